@@ -0,0 +1,559 @@
+// R Commerce embedded admin
+//
+// Talks to the admin API with the signed-in staff member's bearer token,
+// kept in sessionStorage so it is gone when the tab closes. Everything
+// from the API is rendered with textContent, never as HTML.
+(function () {
+  "use strict";
+
+  var API = "/api/v1";
+  var TOKEN_KEY = "rcommerce_admin_token";
+  var EMAIL_KEY = "rcommerce_admin_email";
+  var PAGE_SIZE = 25;
+
+  var state = { offset: 0, total: 0, locations: null };
+
+  function $(id) {
+    return document.getElementById(id);
+  }
+
+  function el(tag, text, className) {
+    var node = document.createElement(tag);
+    if (text !== undefined && text !== null) node.textContent = String(text);
+    if (className) node.className = className;
+    return node;
+  }
+
+  function row(cells) {
+    var tr = document.createElement("tr");
+    cells.forEach(function (cell) {
+      if (cell instanceof Node) {
+        var td = document.createElement("td");
+        td.appendChild(cell);
+        tr.appendChild(td);
+      } else if (cell && typeof cell === "object") {
+        tr.appendChild(el("td", cell.text, cell.className));
+      } else {
+        tr.appendChild(el("td", cell));
+      }
+    });
+    return tr;
+  }
+
+  function emptyRow(tbody, columns, text) {
+    var td = el("td", text, "empty");
+    td.colSpan = columns;
+    var tr = document.createElement("tr");
+    tr.appendChild(td);
+    tbody.appendChild(tr);
+  }
+
+  function clear(node) {
+    while (node.firstChild) node.removeChild(node.firstChild);
+  }
+
+  function money(amount, currency) {
+    return amount === null || amount === undefined ? "" : amount + " " + (currency || "");
+  }
+
+  function date(value) {
+    return value ? new Date(value).toLocaleString() : "";
+  }
+
+  function flash(message, kind) {
+    var node = $("flash");
+    node.textContent = message;
+    node.className = kind || "ok";
+    node.hidden = !message;
+  }
+
+  function token() {
+    return sessionStorage.getItem(TOKEN_KEY);
+  }
+
+  function signOut(message) {
+    sessionStorage.removeItem(TOKEN_KEY);
+    sessionStorage.removeItem(EMAIL_KEY);
+    if (message) flash(message, "error");
+    location.hash = "#/login";
+    route();
+  }
+
+  // Call the API; resolves with the parsed body, rejects with an Error
+  function api(method, path, body) {
+    var headers = { Accept: "application/json" };
+    if (token()) headers.Authorization = "Bearer " + token();
+    if (body !== undefined) headers["Content-Type"] = "application/json";
+    return fetch(API + path, {
+      method: method,
+      headers: headers,
+      body: body === undefined ? undefined : JSON.stringify(body),
+      credentials: "omit",
+      cache: "no-store",
+    }).then(function (response) {
+      if (response.status === 401 && path.indexOf("/auth/") !== 0) {
+        signOut("Your session has expired; sign in again.");
+        throw new Error("Not signed in");
+      }
+      return response.text().then(function (text) {
+        var data = null;
+        try {
+          data = text ? JSON.parse(text) : null;
+        } catch (e) {
+          data = null;
+        }
+        if (!response.ok) {
+          var message = (data && data.error && (data.error.message || data.error)) || response.statusText;
+          if (response.status === 403 && String(message).indexOf("Two-factor") !== 0) {
+            message = "You don't have permission to do that.";
+          }
+          var error = new Error(typeof message === "string" ? message : "Request failed");
+          error.status = response.status;
+          throw error;
+        }
+        return { status: response.status, data: data };
+      });
+    });
+  }
+
+  function failed(error) {
+    if (error && error.message !== "Not signed in") flash(error.message, "error");
+  }
+
+  function formData(form) {
+    var data = {};
+    new FormData(form).forEach(function (value, key) {
+      data[key] = typeof value === "string" ? value.trim() : value;
+    });
+    return data;
+  }
+
+  function lines(value) {
+    return value
+      .split(/[\n,]/)
+      .map(function (entry) {
+        return entry.trim();
+      })
+      .filter(Boolean);
+  }
+
+  // Routing: #/orders, #/orders/<id>, #/stock, #/api-keys
+  var SECTIONS = ["login", "orders", "order", "stock", "api-keys"];
+
+  function show(section) {
+    SECTIONS.forEach(function (id) {
+      $(id).hidden = id !== section;
+    });
+    $("nav").hidden = section === "login";
+    Array.prototype.forEach.call(document.querySelectorAll("nav a"), function (link) {
+      link.classList.toggle("active", location.hash.indexOf(link.getAttribute("href")) === 0);
+    });
+  }
+
+  function route() {
+    var hash = location.hash || "#/orders";
+    if (!token()) {
+      show("login");
+      return;
+    }
+    $("who").textContent = sessionStorage.getItem(EMAIL_KEY) || "";
+    var parts = hash.replace(/^#\//, "").split("/");
+    if (parts[0] === "orders" && parts[1]) {
+      show("order");
+      loadOrder(parts[1]);
+    } else if (parts[0] === "stock") {
+      show("stock");
+      loadLocations();
+    } else if (parts[0] === "api-keys") {
+      show("api-keys");
+      loadKeys();
+    } else {
+      show("orders");
+      loadOrders();
+    }
+  }
+
+  // Sign in; accounts with 2FA get a challenge to answer with a code
+  var challengeToken = null;
+
+  function twoFactorStep(active) {
+    $("login-form").hidden = active;
+    $("two-factor-form").hidden = !active;
+    if (active) $("two-factor-form").elements.code.focus();
+  }
+
+  function signedIn(result) {
+    sessionStorage.setItem(TOKEN_KEY, result.data.access_token);
+    sessionStorage.setItem(EMAIL_KEY, result.data.customer.email);
+    flash("");
+    // Only staff get past the admin routes
+    return api("GET", "/admin/orders?limit=1").then(function () {
+      location.hash = "#/orders";
+      route();
+    });
+  }
+
+  function signInFailed(error) {
+    if (error.status === 403) {
+      sessionStorage.removeItem(TOKEN_KEY);
+      var twoFactor = error.message.indexOf("Two-factor") === 0;
+      flash(twoFactor ? error.message : "This account has no staff access.", "error");
+      return;
+    }
+    failed(error);
+  }
+
+  $("login-form").addEventListener("submit", function (event) {
+    event.preventDefault();
+    var data = formData(event.target);
+    api("POST", "/auth/login", { email: data.email, password: data.password })
+      .then(function (result) {
+        event.target.reset();
+        if (result.status === 202) {
+          challengeToken = result.data.challenge_token;
+          flash("");
+          twoFactorStep(true);
+          return;
+        }
+        return signedIn(result);
+      })
+      .catch(signInFailed);
+  });
+
+  $("two-factor-form").addEventListener("submit", function (event) {
+    event.preventDefault();
+    var code = formData(event.target).code;
+    var body = { challenge_token: challengeToken };
+    // Authenticator codes are digits; backup codes have letters
+    if (/^[0-9 ]+$/.test(code)) body.code = code;
+    else body.backup_code = code;
+    api("POST", "/auth/2fa/verify", body)
+      .then(function (result) {
+        event.target.reset();
+        challengeToken = null;
+        twoFactorStep(false);
+        return signedIn(result);
+      })
+      .catch(function (error) {
+        event.target.reset();
+        if (error.status === 401 && error.message.indexOf("expired") !== -1) {
+          challengeToken = null;
+          twoFactorStep(false);
+        }
+        signInFailed(error);
+      });
+  });
+
+  $("logout").addEventListener("click", function () {
+    signOut();
+    flash("Signed out.");
+  });
+
+  // Orders
+  function loadOrders() {
+    var filter = formData($("order-filter"));
+    var query = "?limit=" + PAGE_SIZE + "&offset=" + state.offset;
+    if (filter.q) query += "&q=" + encodeURIComponent(filter.q);
+    if (filter.status) query += "&status=" + encodeURIComponent(filter.status);
+    api("GET", "/admin/orders" + query)
+      .then(function (result) {
+        var tbody = $("order-rows");
+        clear(tbody);
+        state.total = result.data.meta.total;
+        result.data.orders.forEach(function (order) {
+          var tr = row([
+            order.order_number,
+            order.customer_email,
+            order.status,
+            order.payment_status,
+            order.fulfillment_status,
+            { text: money(order.total, order.currency), className: "num" },
+            date(order.created_at),
+          ]);
+          tr.className = "link";
+          tr.addEventListener("click", function () {
+            location.hash = "#/orders/" + order.id;
+          });
+          tbody.appendChild(tr);
+        });
+        if (!result.data.orders.length) emptyRow(tbody, 7, "No orders found");
+        var page = Math.floor(state.offset / PAGE_SIZE) + 1;
+        var pages = Math.max(1, Math.ceil(state.total / PAGE_SIZE));
+        $("orders-page").textContent = "Page " + page + " of " + pages + " (" + state.total + " orders)";
+        $("orders-prev").disabled = state.offset === 0;
+        $("orders-next").disabled = state.offset + PAGE_SIZE >= state.total;
+      })
+      .catch(failed);
+  }
+
+  $("order-filter").addEventListener("submit", function (event) {
+    event.preventDefault();
+    state.offset = 0;
+    loadOrders();
+  });
+  $("orders-prev").addEventListener("click", function () {
+    state.offset = Math.max(0, state.offset - PAGE_SIZE);
+    loadOrders();
+  });
+  $("orders-next").addEventListener("click", function () {
+    state.offset += PAGE_SIZE;
+    loadOrders();
+  });
+
+  function loadOrder(id) {
+    $("refund-form").hidden = true;
+    api("GET", "/admin/orders/" + encodeURIComponent(id))
+      .then(function (result) {
+        var order = result.data.order;
+        $("order-title").textContent = "Order " + order.order_number;
+
+        var summary = $("order-summary");
+        clear(summary);
+        [
+          ["Customer", order.customer_email],
+          ["Status", order.status],
+          ["Payment", order.payment_status],
+          ["Fulfillment", order.fulfillment_status],
+          ["Subtotal", money(order.subtotal, order.currency)],
+          ["Tax", money(order.tax_total, order.currency)],
+          ["Shipping", money(order.shipping_total, order.currency)],
+          ["Total", money(order.total, order.currency)],
+          ["Placed", date(order.created_at)],
+        ].forEach(function (pair) {
+          summary.appendChild(el("dt", pair[0]));
+          summary.appendChild(el("dd", pair[1]));
+        });
+
+        var items = $("order-items");
+        clear(items);
+        order.items.forEach(function (item) {
+          items.appendChild(
+            row([
+              item.name,
+              item.sku || "",
+              { text: item.quantity, className: "num" },
+              { text: money(item.price, order.currency), className: "num" },
+              { text: money(item.total, order.currency), className: "num" },
+            ])
+          );
+        });
+        if (!order.items.length) emptyRow(items, 5, "No items");
+
+        var payments = $("order-payments");
+        clear(payments);
+        result.data.payments.forEach(function (payment) {
+          var action = el("span");
+          var refundable = payment.status === "paid" && Number(payment.refunded) < Number(payment.amount);
+          if (refundable) {
+            var button = el("button", "Refund", "danger");
+            button.type = "button";
+            button.addEventListener("click", function () {
+              var form = $("refund-form");
+              form.reset();
+              form.elements.payment_id.value = payment.id;
+              form.hidden = false;
+              form.elements.amount.focus();
+            });
+            action = button;
+          }
+          payments.appendChild(
+            row([
+              payment.gateway,
+              payment.gateway_payment_id || "",
+              payment.status,
+              { text: money(payment.amount, payment.currency), className: "num" },
+              { text: money(payment.refunded, payment.currency), className: "num" },
+              action,
+            ])
+          );
+        });
+        if (!result.data.payments.length) emptyRow(payments, 6, "No payments recorded");
+      })
+      .catch(failed);
+  }
+
+  $("refund-form").addEventListener("submit", function (event) {
+    event.preventDefault();
+    var data = formData(event.target);
+    var body = { reason: data.reason };
+    if (data.amount) body.amount = data.amount;
+    var what = data.amount ? data.amount : "everything left on this payment";
+    if (!window.confirm("Refund " + what + "? This cannot be undone.")) return;
+    api("POST", "/admin/payments/" + encodeURIComponent(data.payment_id) + "/refund", body)
+      .then(function (result) {
+        flash("Refunded " + money(result.data.amount, result.data.currency) + ".");
+        route();
+      })
+      .catch(failed);
+  });
+  $("refund-cancel").addEventListener("click", function () {
+    $("refund-form").hidden = true;
+  });
+
+  // Stock
+  function loadLocations() {
+    if (state.locations) return;
+    api("GET", "/admin/inventory/locations")
+      .then(function (result) {
+        state.locations = result.data;
+        var select = $("adjust-form").elements.location_id;
+        clear(select);
+        result.data.forEach(function (location) {
+          var option = el("option", location.name + " (" + location.code + ")");
+          option.value = location.id;
+          select.appendChild(option);
+        });
+      })
+      .catch(failed);
+  }
+
+  function lookupStock() {
+    var value = formData($("stock-lookup")).sku;
+    if (!value) return;
+    var isId = /^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/i.test(value);
+    var query = isId ? "product_id=" + value : "sku=" + encodeURIComponent(value);
+    api("GET", "/admin/inventory/levels?" + query)
+      .then(function (result) {
+        var tbody = $("stock-rows");
+        clear(tbody);
+        result.data.forEach(function (level) {
+          var button = el("button", "Adjust");
+          button.type = "button";
+          button.addEventListener("click", function () {
+            var form = $("adjust-form");
+            form.reset();
+            form.elements.product_id.value = level.product_id;
+            form.elements.variant_id.value = level.variant_id || "";
+            form.elements.location_id.value = level.location_id;
+            $("adjust-title").textContent =
+              "Adjust " + level.product_title + (level.variant_title ? " / " + level.variant_title : "");
+            form.hidden = false;
+            form.elements.quantity_change.focus();
+          });
+          tbody.appendChild(
+            row([
+              level.product_title,
+              level.variant_title || "",
+              level.sku || "",
+              level.location_name,
+              { text: level.available, className: "num" },
+              { text: level.reserved, className: "num" },
+              { text: level.incoming, className: "num" },
+              button,
+            ])
+          );
+        });
+        if (!result.data.length) emptyRow(tbody, 8, "No stock recorded for " + value);
+      })
+      .catch(failed);
+  }
+
+  $("stock-lookup").addEventListener("submit", function (event) {
+    event.preventDefault();
+    $("adjust-form").hidden = true;
+    lookupStock();
+  });
+
+  $("adjust-form").addEventListener("submit", function (event) {
+    event.preventDefault();
+    var data = formData(event.target);
+    var body = {
+      product_id: data.product_id,
+      variant_id: data.variant_id || null,
+      location_id: data.location_id,
+      quantity_change: parseInt(data.quantity_change, 10),
+      reason: data.reason,
+      notes: data.notes || null,
+    };
+    if (!body.quantity_change) {
+      flash("Enter a non-zero change.", "error");
+      return;
+    }
+    api("POST", "/inventory/adjustments", body)
+      .then(function (result) {
+        event.target.hidden = true;
+        if (result.status === 202) {
+          flash("The adjustment is waiting for approval before it is posted.");
+        } else {
+          flash("Stock adjusted by " + body.quantity_change + ".");
+        }
+        lookupStock();
+      })
+      .catch(failed);
+  });
+  $("adjust-cancel").addEventListener("click", function () {
+    $("adjust-form").hidden = true;
+  });
+
+  // API keys (global admin only; they live under /admin/admin/api-keys)
+  var KEYS = "/admin/admin/api-keys";
+
+  function loadKeys() {
+    api("GET", KEYS)
+      .then(function (result) {
+        var tbody = $("key-rows");
+        clear(tbody);
+        result.data.api_keys.forEach(function (key) {
+          var action = el("span");
+          if (key.is_active) {
+            var button = el("button", "Revoke", "danger");
+            button.type = "button";
+            button.addEventListener("click", function () {
+              var reason = window.prompt("Revoke " + key.name + " (" + key.key_prefix + ")? Reason:");
+              if (reason === null) return;
+              api("POST", KEYS + "/" + encodeURIComponent(key.key_prefix) + "/revoke", { reason: reason })
+                .then(function () {
+                  flash("Revoked " + key.key_prefix + ".");
+                  loadKeys();
+                })
+                .catch(failed);
+            });
+            action = button;
+          }
+          tbody.appendChild(
+            row([
+              key.name,
+              key.key_prefix,
+              key.key_type,
+              key.scopes.join(", "),
+              key.last_used_at ? date(key.last_used_at) : "Never",
+              el("span", key.is_active ? "active" : "revoked", key.is_active ? "badge" : "badge revoked"),
+              action,
+            ])
+          );
+        });
+        if (!result.data.api_keys.length) emptyRow(tbody, 7, "No API keys yet");
+      })
+      .catch(failed);
+  }
+
+  $("key-form").addEventListener("submit", function (event) {
+    event.preventDefault();
+    var data = formData(event.target);
+    var body = {
+      name: data.name,
+      publishable: data.type === "publishable",
+      scopes: lines(data.scopes || ""),
+      origins: lines(data.origins || ""),
+      allowed_ips: lines(data.allowed_ips || ""),
+    };
+    if (data.expires_days) body.expires_days = parseInt(data.expires_days, 10);
+    api("POST", KEYS, body)
+      .then(function (result) {
+        event.target.reset();
+        $("new-key-value").textContent = result.data.key;
+        $("new-key").hidden = false;
+        flash("Created " + result.data.api_key.key_prefix + ".");
+        loadKeys();
+      })
+      .catch(failed);
+  });
+
+  window.addEventListener("hashchange", function () {
+    $("new-key").hidden = true;
+    $("new-key-value").textContent = "";
+    flash("");
+    route();
+  });
+  route();
+})();
@@ -0,0 +1,89 @@
+//! Embedded admin UI
+//!
+//! A small single-page admin for self-hosters without a separate admin
+//! frontend: browse orders, refund payments, adjust stock and manage API
+//! keys. The HTML, script and stylesheet are compiled into the binary and
+//! served at `admin_ui.path`. The page holds no data; staff sign in through
+//! `/api/v1/auth/login` and the script calls the admin API with their
+//! bearer token, so `admin_middleware` checks every action against their
+//! staff permissions as it does for any other client.
+
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::state::AppState;
+use rcommerce_core::config::AdminUiConfig;
+
+const INDEX_HTML: &str = include_str!("../admin-ui/index.html");
+const APP_JS: &str = include_str!("../admin-ui/app.js");
+const APP_CSS: &str = include_str!("../admin-ui/app.css");
+
+/// Placeholder in `index.html` replaced with the configured path
+const BASE_PLACEHOLDER: &str = "{{BASE}}";
+
+/// Scripts and styles only from the binary, API calls only to this origin
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'self'; style-src 'self'; \
+     connect-src 'self'; img-src 'self' data:; base-uri 'none'; form-action 'none'; frame-ancestors 'none'";
+
+/// The UI at `config.path`, or no routes when it is disabled
+pub fn router(config: &AdminUiConfig) -> Router<AppState> {
+    if !config.enabled {
+        return Router::new();
+    }
+    let base = config.path.clone();
+    let index = INDEX_HTML.replace(BASE_PLACEHOLDER, &base);
+    let page = move || {
+        let index = index.clone();
+        async move { asset("text/html; charset=utf-8", index) }
+    };
+    Router::new()
+        .route(&base, get(page.clone()))
+        .route(&format!("{}/", base), get(page))
+        .route(
+            &format!("{}/app.js", base),
+            get(|| async { asset("text/javascript; charset=utf-8", APP_JS.to_string()) }),
+        )
+        .route(
+            &format!("{}/app.css", base),
+            get(|| async { asset("text/css; charset=utf-8", APP_CSS.to_string()) }),
+        )
+}
+
+/// Revalidated on every load, so an upgraded binary serves its own UI at once
+fn asset(content_type: &'static str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "no-cache"),
+            (header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_references_base_path() {
+        let index = INDEX_HTML.replace(BASE_PLACEHOLDER, "/staff");
+        assert!(index.contains("/staff/app.js"));
+        assert!(index.contains("/staff/app.css"));
+        assert!(!index.contains(BASE_PLACEHOLDER));
+    }
+
+    #[test]
+    fn test_assets_load_nothing_external() {
+        // The CSP allows only same-origin scripts, styles and API calls
+        for asset in [INDEX_HTML, APP_JS, APP_CSS] {
+            assert!(!asset.contains("http://") && !asset.contains("https://"));
+        }
+        assert!(!INDEX_HTML.contains("<script>"), "inline scripts are blocked by the CSP");
+    }
+}
@@ -0,0 +1,283 @@
+//! Domain event and webhook payload catalog
+//!
+//! JSON Schemas (draft 2020-12) of the domain events recorded in
+//! `order_events` and `purchase_order_events`, of the events published on
+//! the event bus (the `data` of their webhooks), and of the webhook payloads
+//! the platform sends, generated from the Rust types like the OpenAPI
+//! document. Served at `/api/v1/admin/events/schema` and written by
+//! `rcommerce event-schema`, so integrators can validate payloads and
+//! generate types (e.g. with quicktype or json-schema-to-typescript).
+//!
+//! `version` changes when a payload changes incompatibly (a field removed,
+//! renamed or retyped); `fingerprint` changes with any change to the
+//! catalog.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use rcommerce_core::events::{LowStock, OrderCreated, PaymentUpdated, ShipmentStatusChanged};
+use rcommerce_core::inventory::supplier_feed::SIGNATURE_HEADER as FEED_SIGNATURE_HEADER;
+use rcommerce_core::inventory::{DropshipOrderPayload, PurchaseOrderEvent, PurchaseOrderEventType};
+use rcommerce_core::order::OrderEvent;
+use rcommerce_core::services::WebhookEnvelope;
+use rcommerce_core::websocket::StockUpdate;
+
+/// Version of the event payloads; bump when one changes incompatibly
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Header carrying the HMAC-SHA256 signature of webhook deliveries
+const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Schemas referenced by the catalog, under `$defs`
+#[derive(Default)]
+struct Definitions(BTreeMap<String, Value>);
+
+impl Definitions {
+    /// Add `T` and the schemas it references; returns a `$ref` to it
+    fn add<T: ToSchema>(&mut self) -> Value {
+        let mut schemas = Vec::new();
+        T::schemas(&mut schemas);
+        for (name, schema) in schemas {
+            self.0.insert(name, to_json_schema(serde_json::to_value(schema).unwrap_or_default()));
+        }
+        let name = T::name().into_owned();
+        self.0
+            .insert(name.clone(), to_json_schema(serde_json::to_value(T::schema()).unwrap_or_default()));
+        json!({ "$ref": format!("#/$defs/{}", name) })
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+}
+
+/// Point OpenAPI component references at `$defs`
+fn to_json_schema(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("$ref", Value::String(target)) => (
+                        key,
+                        Value::String(target.replace("#/components/schemas/", "#/$defs/")),
+                    ),
+                    (_, value) => (key, to_json_schema(value)),
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(to_json_schema).collect()),
+        other => other,
+    }
+}
+
+/// The `type` tag of a variant of an internally tagged enum's schema
+fn variant_tag(variant: &Value) -> Option<&str> {
+    let tag = &variant["properties"]["type"];
+    tag["enum"][0].as_str().or_else(|| tag["const"].as_str())
+}
+
+fn build() -> Value {
+    let mut defs = Definitions::default();
+    let mut events = Vec::new();
+
+    // One schema per order event, split from the tagged enum's variants
+    defs.add::<OrderEvent>();
+    let order_events = defs
+        .get(&OrderEvent::name())
+        .and_then(|schema| schema["oneOf"].as_array())
+        .cloned()
+        .unwrap_or_default();
+    for variant in order_events {
+        let Some(name) = variant_tag(&variant).map(str::to_string) else {
+            continue;
+        };
+        events.push(json!({
+            "name": name,
+            "source": "order_events",
+            "description": variant["description"],
+            "schema": variant,
+        }));
+    }
+
+    let purchase_order_event = defs.add::<PurchaseOrderEvent>();
+    for event in PurchaseOrderEventType::ALL {
+        events.push(json!({
+            "name": event.as_str(),
+            "source": "purchase_order_events",
+            "description": event.description(),
+            "schema": {
+                "allOf": [
+                    purchase_order_event,
+                    { "properties": { "event": { "const": event.as_str() } } },
+                ],
+            },
+        }));
+    }
+
+    let bus_events = [
+        ("order.created", "An order was placed", defs.add::<OrderCreated>()),
+        ("payment.updated", "A payment was made or changed status", defs.add::<PaymentUpdated>()),
+        ("inventory.low_stock", "Available stock at a location fell to its reorder point", defs.add::<LowStock>()),
+        ("shipment.status_changed", "A shipment moved on", defs.add::<ShipmentStatusChanged>()),
+        (
+            "stock.availability_changed",
+            "A product or variant went out of stock or came back",
+            defs.add::<StockUpdate>(),
+        ),
+    ];
+    for (name, description, schema) in bus_events {
+        events.push(json!({
+            "name": name,
+            "source": "event_bus",
+            "description": description,
+            "schema": schema,
+        }));
+    }
+
+    let webhooks = vec![
+        json!({
+            "name": "webhook_event",
+            "description": "POSTed to webhook endpoints for the event types they subscribe to (the `payload` of \
+                            endpoints with a payload template)",
+            "signature_header": WEBHOOK_SIGNATURE_HEADER,
+            "schema": defs.add::<WebhookEnvelope>(),
+        }),
+        json!({
+            "name": "dropship_order",
+            "description": "POSTed to a dropship supplier's order webhook",
+            "signature_header": FEED_SIGNATURE_HEADER,
+            "schema": defs.add::<DropshipOrderPayload>(),
+        }),
+    ];
+
+    let body = json!({
+        "events": events,
+        "webhooks": webhooks,
+        "$defs": defs.0,
+    });
+    let fingerprint = hex::encode(Sha256::digest(body.to_string().as_bytes()));
+
+    let mut catalog = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "version": EVENT_SCHEMA_VERSION,
+        "api_version": env!("CARGO_PKG_VERSION"),
+        "fingerprint": fingerprint,
+        "signatures": "sha256=<hex HMAC-SHA256 of the request body with the endpoint's secret>",
+    });
+    if let (Value::Object(catalog), Value::Object(body)) = (&mut catalog, body) {
+        catalog.extend(body);
+    }
+    catalog
+}
+
+/// The catalog
+pub fn catalog() -> &'static Value {
+    static CATALOG: OnceLock<Value> = OnceLock::new();
+    CATALOG.get_or_init(build)
+}
+
+/// The catalog as pretty-printed JSON
+pub fn catalog_json() -> String {
+    serde_json::to_string_pretty(catalog()).expect("event schema catalog serializes to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    /// Every `$ref` in `value`
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(target)) => found.push(target),
+                        _ => refs(value, found),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    fn event<'a>(catalog: &'a Value, source: &str, name: &str) -> &'a Value {
+        catalog["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|event| event["source"] == source && event["name"] == name)
+            .unwrap_or_else(|| panic!("missing {} {}", source, name))
+    }
+
+    #[test]
+    fn test_catalog_covers_events() {
+        let catalog = catalog();
+        assert_eq!(catalog["version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(catalog["fingerprint"].as_str().unwrap().len(), 64);
+
+        let created = OrderEvent::OrderCreated {
+            order_id: Uuid::new_v4(),
+            customer_id: None,
+            total: Decimal::new(1000, 2),
+            currency: "EUR".to_string(),
+        };
+        let entry = event(catalog, "order_events", created.event_type());
+        assert_eq!(entry["description"], "An order was placed");
+        let required = entry["schema"]["required"].as_array().unwrap();
+        for field in ["type", "order_id", "total", "currency"] {
+            assert!(required.iter().any(|name| name == field), "{} not required", field);
+        }
+        // Every variant has its own entry
+        assert_eq!(catalog["events"].as_array().unwrap().iter().filter(|e| e["source"] == "order_events").count(), 10);
+
+        for event_type in PurchaseOrderEventType::ALL {
+            event(catalog, "purchase_order_events", event_type.as_str());
+        }
+        for name in rcommerce_core::events::DomainEvent::NAMES {
+            event(catalog, "event_bus", name);
+        }
+        let webhooks: Vec<&str> = catalog["webhooks"].as_array().unwrap().iter().map(|w| w["name"].as_str().unwrap()).collect();
+        assert_eq!(webhooks, ["webhook_event", "dropship_order"]);
+    }
+
+    #[test]
+    fn test_catalog_refs_resolve() {
+        let catalog = catalog();
+        let mut found = Vec::new();
+        refs(catalog, &mut found);
+        assert!(!found.is_empty());
+        for target in found {
+            let name = target.strip_prefix("#/$defs/").expect("$defs ref");
+            assert!(catalog["$defs"].get(name).is_some(), "unresolved {}", target);
+        }
+    }
+
+    #[test]
+    fn test_payloads_match_schemas() {
+        let catalog = catalog();
+        let envelope = serde_json::to_value(WebhookEnvelope::test("order.created")).unwrap();
+        let schema = &catalog["$defs"]["WebhookEnvelope"];
+        for field in schema["required"].as_array().unwrap() {
+            assert!(envelope.get(field.as_str().unwrap()).is_some(), "{} missing", field);
+        }
+        assert_eq!(envelope["test"], true);
+
+        let event = serde_json::to_value(OrderEvent::OrderDelivered { order_id: Uuid::new_v4(), delivered_at: Utc::now() }).unwrap();
+        let schema = event_schema(catalog, "order_delivered");
+        for key in event.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(key).is_some(), "{} not in schema", key);
+        }
+    }
+
+    fn event_schema<'a>(catalog: &'a Value, name: &str) -> &'a Value {
+        &event(catalog, "order_events", name)["schema"]
+    }
+}
@@ -1,10 +1,16 @@
+pub mod admin_ui;
+pub mod event_schema;
 pub mod middleware;
+pub mod openapi;
 pub mod routes;
+pub mod scheduler;
 pub mod server;
 pub mod state;
 pub mod tls;
+pub mod worker;
 
 pub use server::run;
+pub use worker::run as run_worker;
 pub use state::AppState;
 
 // Re-export commonly used types
@@ -0,0 +1,163 @@
+//! Admin and API key access controls
+//!
+//! Two optional restrictions for locked-down deployments:
+//! - with `[tls.client_auth] require_for_admin`, admin routes refuse
+//!   requests whose TLS connection did not present a client certificate
+//!   signed by the configured CA
+//! - an API key with `allowed_ips` only works from those addresses or
+//!   networks; forwarding headers only count from `server.trusted_proxies`
+//!
+//! Denials are answered with 403, logged, and recorded in the
+//! `access_denials` audit table.
+
+use axum::{body::Body, extract::Request};
+use std::sync::Arc;
+
+use crate::tls::TlsClientAuth;
+use rcommerce_core::models::{AccessDenialReason, NewAccessDenial};
+use rcommerce_core::repository::{AccessDenialRepository, ApiKeyRecord};
+
+/// Start a denial record for `request`
+pub fn denial(request: &Request<Body>, reason: AccessDenialReason) -> NewAccessDenial {
+    NewAccessDenial {
+        reason,
+        method: request.method().to_string(),
+        path: request.uri().path().to_string(),
+        client_ip: crate::middleware::geoip::client_ip(request).map(|ip| ip.to_string()),
+        api_key_id: None,
+        customer_id: None,
+        detail: None,
+    }
+}
+
+/// Denial for a request without a verified client certificate
+pub fn client_certificate_denial(request: &Request<Body>) -> Option<NewAccessDenial> {
+    let verified = request
+        .extensions()
+        .get::<TlsClientAuth>()
+        .is_some_and(TlsClientAuth::is_verified);
+    if verified {
+        return None;
+    }
+    let mut denial = denial(request, AccessDenialReason::ClientCertificateRequired);
+    denial.detail = Some("No verified TLS client certificate".to_string());
+    Some(denial)
+}
+
+/// Denial for an API key used from outside its IP allowlist
+pub fn api_key_ip_denial(request: &Request<Body>, record: &ApiKeyRecord) -> Option<NewAccessDenial> {
+    if record.allowed_ips.is_empty() {
+        return None;
+    }
+    let ip = crate::middleware::geoip::client_ip(request);
+    if ip.is_some_and(|ip| record.allows_ip(ip)) {
+        return None;
+    }
+    let mut denial = denial(request, AccessDenialReason::IpNotAllowed);
+    denial.api_key_id = Some(record.id);
+    denial.customer_id = record.customer_id;
+    denial.detail = Some(format!("API key '{}' is restricted to {}", record.key_prefix, record.allowed_ips.join(", ")));
+    Some(denial)
+}
+
+/// Log a denial and record it in the audit log (fire and forget)
+pub fn record_denial<R: AccessDenialRepository + 'static>(repo: &Arc<R>, denial: NewAccessDenial) {
+    tracing::warn!(
+        "Access denied ({}): {} {} from {}",
+        denial.reason.as_str(),
+        denial.method,
+        denial.path,
+        denial.client_ip.as_deref().unwrap_or("unknown address")
+    );
+    let repo = repo.clone();
+    tokio::spawn(async move {
+        if let Err(e) = repo.record(&denial).await {
+            tracing::error!("Failed to record access denial: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rcommerce_core::repository::ApiKeyType;
+
+    fn key(allowed_ips: &[&str]) -> ApiKeyRecord {
+        ApiKeyRecord {
+            id: uuid::Uuid::new_v4(),
+            customer_id: None,
+            key_prefix: "ak_test".to_string(),
+            key_hash: String::new(),
+            name: "Integration".to_string(),
+            scopes: vec!["orders:read".to_string()],
+            expires_at: None,
+            last_used_at: None,
+            last_used_ip: None,
+            rate_limit_per_minute: None,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            revoked_at: None,
+            revoked_reason: None,
+            key_type: ApiKeyType::Secret,
+            allowed_origins: Vec::new(),
+            rotated_to: None,
+            allowed_ips: allowed_ips.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn request_from(peer: &str) -> Request<Body> {
+        let mut request = Request::builder().uri("/api/v1/orders").body(Body::empty()).unwrap();
+        let peer = format!("{}:51000", peer).parse::<std::net::SocketAddr>().unwrap();
+        request.extensions_mut().insert(axum::extract::ConnectInfo(peer));
+        request
+    }
+
+    #[test]
+    fn test_api_key_ip_denial() {
+        assert!(api_key_ip_denial(&request_from("198.51.100.1"), &key(&[])).is_none());
+        assert!(api_key_ip_denial(&request_from("10.1.2.3"), &key(&["10.0.0.0/8"])).is_none());
+
+        let denial = api_key_ip_denial(&request_from("198.51.100.1"), &key(&["10.0.0.0/8"])).unwrap();
+        assert_eq!(denial.reason, AccessDenialReason::IpNotAllowed);
+        assert_eq!(denial.client_ip.as_deref(), Some("198.51.100.1"));
+        assert_eq!(denial.path, "/api/v1/orders");
+
+        // An unknown address never matches an allowlist
+        let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        assert!(api_key_ip_denial(&request, &key(&["10.0.0.0/8"])).is_some());
+    }
+
+    #[test]
+    fn test_api_key_ip_denial_ignores_spoofed_headers() {
+        let spoofed = |mut request: Request<Body>| {
+            request.headers_mut().insert("x-forwarded-for", "10.1.2.3".parse().unwrap());
+            request.headers_mut().insert("x-real-ip", "10.1.2.3".parse().unwrap());
+            request
+        };
+
+        // A stolen key sent directly with an allowed address in the headers
+        let denial = api_key_ip_denial(&spoofed(request_from("198.51.100.1")), &key(&["10.0.0.0/8"])).unwrap();
+        assert_eq!(denial.client_ip.as_deref(), Some("198.51.100.1"));
+
+        // The same headers from a trusted proxy name the client
+        let mut request = spoofed(request_from("192.0.2.10"));
+        request
+            .extensions_mut()
+            .insert(crate::middleware::geoip::TrustedProxies::new(vec!["192.0.2.10".to_string()]));
+        assert!(api_key_ip_denial(&request, &key(&["10.0.0.0/8"])).is_none());
+    }
+
+    #[test]
+    fn test_client_certificate_denial() {
+        let mut request = Request::builder().uri("/api/v1/admin/orders").body(Body::empty()).unwrap();
+        assert!(client_certificate_denial(&request).is_some());
+
+        request.extensions_mut().insert(TlsClientAuth::default());
+        assert!(client_certificate_denial(&request).is_some());
+
+        request.extensions_mut().insert(TlsClientAuth { certificate_fingerprint: Some("ab".repeat(32)) });
+        assert!(client_certificate_denial(&request).is_none());
+    }
+}
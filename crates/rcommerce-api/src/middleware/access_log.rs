@@ -0,0 +1,101 @@
+//! Access log of API requests
+//!
+//! When `[access_log]` is enabled, every request outside `exclude_paths` is
+//! recorded with its status, latency, API key prefix and truncated client IP.
+//! Entries are buffered and written every `flush_interval_secs`; search them
+//! with `GET /api/v1/admin/access-log`.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use std::time::Instant;
+
+use crate::state::AppState;
+use rcommerce_core::models::{truncate_ip, NewAccessLogEntry};
+
+/// Public prefix of the API key in the Authorization header; secrets and
+/// JWTs are never taken
+fn api_key_prefix(request: &Request<Body>) -> Option<String> {
+    let auth_header = request.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let api_key = super::api_key_auth::extract_api_key(auth_header)?;
+    let (prefix, _) = api_key.split_once('.')?;
+    Some(prefix.trim().to_string())
+}
+
+/// Access log middleware - records requests when the access log is enabled
+pub async fn access_log_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let access_log = &state.access_log;
+    if !access_log.is_logged(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let start = Instant::now();
+    let created_at = Utc::now();
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let api_key_prefix = api_key_prefix(&request)
+        .or_else(|| super::storefront_key::storefront_key(&request).and_then(|key| {
+            key.split_once('.').map(|(prefix, _)| prefix.to_string())
+        }));
+    let client_network = super::geoip::client_ip(&request).map(truncate_ip);
+
+    let response = next.run(request).await;
+
+    access_log.record(NewAccessLogEntry {
+        method,
+        path,
+        status: response.status().as_u16(),
+        latency_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
+        api_key_prefix,
+        client_network,
+        created_at,
+    });
+    response
+}
+
+/// Spawn the periodic write of buffered entries
+pub fn spawn_flush(state: &AppState) {
+    let access_log = state.access_log.clone();
+    if !access_log.config().enabled {
+        return;
+    }
+    let interval = std::time::Duration::from_secs(access_log.config().flush_interval_secs);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = access_log.flush().await {
+                tracing::error!("Failed to write access log: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(authorization: &str) -> Request<Body> {
+        Request::builder()
+            .uri("/api/v1/products")
+            .header(header::AUTHORIZATION, authorization)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_api_key_prefix_never_keeps_secrets() {
+        assert_eq!(api_key_prefix(&request("Bearer ak_partner.s3cr3t")).as_deref(), Some("ak_partner"));
+        assert_eq!(api_key_prefix(&request("ak_partner.s3cr3t")).as_deref(), Some("ak_partner"));
+        assert_eq!(api_key_prefix(&request("Bearer eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.sig")), None);
+    }
+}
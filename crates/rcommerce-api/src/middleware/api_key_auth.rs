@@ -16,9 +16,10 @@ use axum::{
 };
 use std::sync::Arc;
 
+use crate::middleware::access_control;
 use rcommerce_core::{
-    repository::{ApiKeyRepository, PostgresApiKeyRepository},
-    services::{ScopeChecker, Resource, Action, AuthService},
+    repository::{ApiKeyRepository, PostgresAccessDenialRepository, PostgresApiKeyRepository, PostgresLoginSessionRepository},
+    services::{ScopeChecker, Resource, Action, AuthService, LoginSessionService},
 };
 
 /// API key authentication result
@@ -66,8 +67,12 @@ impl ApiKeyAuth {
 /// Expected header format:
 /// - `Authorization: Bearer <prefix>.<secret>` (standard Bearer format)
 /// - `Authorization: <prefix>.<secret>` (direct key format)
+///
+/// Keys with an IP allowlist are refused (403) from other addresses; the
+/// denial is recorded when the access denial repository is available.
 pub async fn api_key_auth_middleware(
     Extension(repo): Extension<Arc<PostgresApiKeyRepository>>,
+    denials: Option<Extension<Arc<PostgresAccessDenialRepository>>>,
     mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -98,6 +103,10 @@ pub async fn api_key_auth_middleware(
 
     // Validate the API key
     let key_record = match repo.verify_key(&api_key).await {
+        Ok(Some(record)) if record.is_publishable() => {
+            tracing::warn!("API key auth: Publishable key used outside the storefront routes");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
         Ok(Some(record)) => record,
         Ok(None) => {
             tracing::warn!("API key auth: Invalid or revoked API key");
@@ -109,13 +118,13 @@ pub async fn api_key_auth_middleware(
         }
     };
 
+    if let Some(denial) = access_control::api_key_ip_denial(&request, &key_record) {
+        deny(denials.as_ref(), denial);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Get client IP for logging
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.split(',').next())
-        .map(|s| s.trim().to_string());
+    let client_ip = crate::middleware::geoip::client_ip(&request).map(|ip| ip.to_string());
 
     // Update last used timestamp (fire and forget)
     let repo_clone = repo.clone();
@@ -146,12 +155,29 @@ pub async fn api_key_auth_middleware(
     Ok(next.run(request).await)
 }
 
+/// Record an IP allowlist denial, or just log it without a repository
+fn deny(
+    denials: Option<&Extension<Arc<PostgresAccessDenialRepository>>>,
+    denial: rcommerce_core::models::NewAccessDenial,
+) {
+    match denials {
+        Some(Extension(repo)) => access_control::record_denial(repo, denial),
+        None => tracing::warn!(
+            "Access denied ({}): {} {} from {}",
+            denial.reason.as_str(),
+            denial.method,
+            denial.path,
+            denial.client_ip.as_deref().unwrap_or("unknown address")
+        ),
+    }
+}
+
 /// Extract API key from Authorization header
 /// 
 /// Handles formats:
 /// - `Bearer <prefix>.<secret>`
 /// - `<prefix>.<secret>`
-fn extract_api_key(auth_header: &str) -> Option<String> {
+pub(crate) fn extract_api_key(auth_header: &str) -> Option<String> {
     // Try Bearer token format first
     if let Some(key) = AuthService::extract_bearer_token(auth_header) {
         // Check if it looks like an API key (has exactly one dot, not JWT format)
@@ -207,6 +233,8 @@ fn looks_like_jwt(token: &str) -> bool {
 pub async fn combined_auth_middleware(
     Extension(repo): Extension<Arc<PostgresApiKeyRepository>>,
     Extension(auth_service): Extension<Arc<rcommerce_core::services::AuthService>>,
+    Extension(login_sessions): Extension<Arc<LoginSessionService<PostgresLoginSessionRepository>>>,
+    denials: Option<Extension<Arc<PostgresAccessDenialRepository>>>,
     mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -229,14 +257,18 @@ pub async fn combined_auth_middleware(
         tracing::debug!("Combined auth: Attempting API key authentication");
         
         match repo.verify_key(&api_key).await {
+            Ok(Some(record)) if record.is_publishable() => {
+                tracing::warn!("Combined auth: Publishable key used outside the storefront routes");
+                return Err(StatusCode::UNAUTHORIZED);
+            }
             Ok(Some(record)) => {
+                if let Some(denial) = access_control::api_key_ip_denial(&request, &record) {
+                    deny(denials.as_ref(), denial);
+                    return Err(StatusCode::FORBIDDEN);
+                }
+
                 // Get client IP for logging
-                let client_ip = request
-                    .headers()
-                    .get("x-forwarded-for")
-                    .and_then(|h| h.to_str().ok())
-                    .and_then(|s| s.split(',').next())
-                    .map(|s| s.trim().to_string());
+                let client_ip = crate::middleware::geoip::client_ip(&request).map(|ip| ip.to_string());
 
                 // Update last used timestamp (fire and forget)
                 let repo_clone = repo.clone();
@@ -282,29 +314,26 @@ pub async fn combined_auth_middleware(
     if let Some(token) = AuthService::extract_bearer_token(auth_header) {
         tracing::debug!("Combined auth: Attempting JWT authentication");
         
-        match auth_service.verify_token(token) {
-            Ok(claims) => {
-                // Create JWT auth context
-                let auth = JwtAuth {
-                    customer_id: claims.sub,
-                    email: claims.email,
-                    permissions: claims.permissions,
-                };
-                
-                request.extensions_mut().insert(auth);
-                
-                tracing::debug!(
-                    "Combined auth: JWT authenticated for customer: {}",
-                    claims.sub
-                );
-                
-                return Ok(next.run(request).await);
-            }
-            Err(e) => {
-                tracing::warn!("Combined auth: JWT verification failed: {}", e);
-                return Err(StatusCode::UNAUTHORIZED);
-            }
-        }
+        let claims = login_sessions
+            .verify_access_token(&auth_service, token, chrono::Utc::now())
+            .await
+            .map_err(super::token_rejection)?;
+
+        // Create JWT auth context
+        let auth = JwtAuth {
+            customer_id: claims.sub,
+            email: claims.email,
+            permissions: claims.permissions,
+        };
+
+        request.extensions_mut().insert(auth);
+
+        tracing::debug!(
+            "Combined auth: JWT authenticated for customer: {}",
+            claims.sub
+        );
+
+        return Ok(next.run(request).await);
     }
 
     tracing::warn!("Combined auth: No valid authentication found");
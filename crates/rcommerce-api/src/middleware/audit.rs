@@ -0,0 +1,114 @@
+//! Audit log of API mutations
+//!
+//! When `[audit]` is enabled, every POST, PUT, PATCH and DELETE behind
+//! authentication is recorded with the acting user or API key, the resource,
+//! the status and client IP. For resources the audit service can snapshot,
+//! the row is read before and after the call and the changed fields stored.
+//! Search entries with `GET /api/v1/admin/audit-log`.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, OriginalUri, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use super::{ApiKeyAuth, JwtAuth};
+use crate::state::AppState;
+use rcommerce_core::models::{AuditActorType, NewAuditEntry};
+use rcommerce_core::services::audit_service::{audit_target, created_id};
+
+/// Responses larger than this aren't read for the ID of a created resource
+const MAX_CREATED_BODY_BYTES: usize = 1024 * 1024;
+
+/// The authenticated caller: user from a JWT or session, else API key
+fn actor(request: &Request<Body>) -> Option<(AuditActorType, uuid::Uuid, String)> {
+    if let Some(auth) = request.extensions().get::<JwtAuth>() {
+        return Some((AuditActorType::User, auth.customer_id, auth.email.clone()));
+    }
+    request
+        .extensions()
+        .get::<ApiKeyAuth>()
+        .map(|key| (AuditActorType::ApiKey, key.key_id, key.name.clone()))
+}
+
+/// Audit middleware - records mutating calls when the audit log is enabled
+///
+/// Runs inside authentication, so the caller is known. A failure to write
+/// the entry is logged but doesn't fail the call, which has already happened.
+pub async fn audit_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    // Nested routers see the path without `/api/v1`; the log keeps the full path
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|original| original.0.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let method = request.method().to_string();
+    let audit = &state.audit;
+    if !audit.is_audited(&method, &path) {
+        return next.run(request).await;
+    }
+    let Some((actor_type, actor_id, actor_label)) = actor(&request) else {
+        return next.run(request).await;
+    };
+
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string());
+    let client_ip = super::geoip::client_ip(&request).map(|ip| ip.to_string());
+    let mut target = audit_target(&method, &path);
+    let mut before = match &target {
+        Some(target) if !target.created => audit.snapshot(target).await,
+        _ => None,
+    };
+
+    let mut response = next.run(request).await;
+    let status = response.status();
+
+    // Failed calls are recorded without changes
+    let mut after = None;
+    if !status.is_success() {
+        before = None;
+    } else if let Some(target) = target.as_mut() {
+        if target.created {
+            let (parts, body) = response.into_parts();
+            let small = HttpBody::size_hint(&body).upper().is_some_and(|len| len <= MAX_CREATED_BODY_BYTES as u64);
+            let body = if small {
+                match axum::body::to_bytes(body, MAX_CREATED_BODY_BYTES).await {
+                    Ok(bytes) => {
+                        target.resource_id = serde_json::from_slice::<serde_json::Value>(&bytes).ok().and_then(|body| created_id(&body));
+                        Body::from(bytes)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Audit: failed to read response of {} {}: {}", method, path, e);
+                        Body::empty()
+                    }
+                }
+            } else {
+                body
+            };
+            response = Response::from_parts(parts, body);
+        }
+        after = audit.snapshot(target).await;
+    }
+
+    let entry = NewAuditEntry {
+        actor_type,
+        actor_id: Some(actor_id),
+        actor_label: Some(actor_label),
+        method,
+        path,
+        route,
+        resource_type: target.as_ref().map(|t| t.resource_type.clone()),
+        resource_id: target.and_then(|t| t.resource_id),
+        status: status.as_u16(),
+        changes: None,
+        client_ip,
+    };
+    if let Err(e) = audit.record(entry, before, after).await {
+        tracing::error!("Failed to write audit log: {}", e);
+    }
+    response
+}
@@ -0,0 +1,323 @@
+//! Traffic capture for debugging production-only issues
+//!
+//! When `[capture]` is enabled, a sampled share of request/response pairs is
+//! recorded into an in-memory ring buffer. Credentials and PII are redacted
+//! before anything is stored: sensitive headers are masked, JSON bodies have
+//! PII fields replaced, and non-JSON bodies are dropped entirely.
+//!
+//! The buffer is downloaded from `GET /api/v1/admin/capture` and replayed
+//! against a staging server with `rcommerce replay`.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::config::CaptureConfig;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Headers that carry credentials and are never stored
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-csrf-token",
+];
+
+/// JSON fields (and query parameters) that hold credentials or PII
+const PII_FIELDS: &[&str] = &[
+    "password",
+    "current_password",
+    "new_password",
+    "password_hash",
+    "token",
+    "access_token",
+    "refresh_token",
+    "secret",
+    "api_key",
+    "email",
+    "phone",
+    "first_name",
+    "last_name",
+    "address1",
+    "address2",
+    "street",
+    "card_number",
+    "cvc",
+    "cvv",
+    "iban",
+    "ip_address",
+    "date_of_birth",
+];
+
+/// Paths never captured (the capture download itself)
+const EXCLUDED_PREFIXES: &[&str] = &["/api/v1/admin/capture"];
+
+/// A recorded request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedExchange {
+    pub id: Uuid,
+    pub captured_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub method: String,
+    /// Path and (redacted) query string
+    pub uri: String,
+    pub request_headers: BTreeMap<String, String>,
+    /// Redacted JSON body; `None` for empty, non-JSON or oversized bodies
+    pub request_body: Option<serde_json::Value>,
+    pub status: u16,
+    pub response_headers: BTreeMap<String, String>,
+    pub response_body: Option<serde_json::Value>,
+}
+
+/// In-memory ring buffer of captured exchanges
+#[derive(Clone)]
+pub struct TrafficCapture {
+    config: Arc<CaptureConfig>,
+    buffer: Arc<Mutex<VecDeque<CapturedExchange>>>,
+}
+
+impl TrafficCapture {
+    pub fn new(config: CaptureConfig) -> Self {
+        let capacity = config.buffer_size;
+        Self {
+            config: Arc::new(config),
+            buffer: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled && self.config.buffer_size > 0
+    }
+
+    /// Decide whether to capture the current request
+    fn should_sample(&self) -> bool {
+        rand::random::<f64>() < self.config.sample_rate
+    }
+
+    /// Append an exchange, evicting the oldest when full
+    pub async fn record(&self, exchange: CapturedExchange) {
+        let mut buffer = self.buffer.lock().await;
+        while buffer.len() >= self.config.buffer_size {
+            buffer.pop_front();
+        }
+        buffer.push_back(exchange);
+    }
+
+    /// Copy of the buffer, oldest first
+    pub async fn snapshot(&self) -> Vec<CapturedExchange> {
+        self.buffer.lock().await.iter().cloned().collect()
+    }
+
+    /// Empty the buffer, returning how many exchanges were dropped
+    pub async fn clear(&self) -> usize {
+        let mut buffer = self.buffer.lock().await;
+        let count = buffer.len();
+        buffer.clear();
+        count
+    }
+
+    fn is_pii_field(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        PII_FIELDS.contains(&name.as_str())
+            || self.config.redact_fields.iter().any(|f| f.eq_ignore_ascii_case(&name))
+    }
+
+    fn redact_headers(&self, headers: &HeaderMap) -> BTreeMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    value.to_str().unwrap_or("[binary]").to_string()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect()
+    }
+
+    fn redact_uri(&self, uri: &axum::http::Uri) -> String {
+        let Some(query) = uri.query() else {
+            return uri.path().to_string();
+        };
+        let query: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if self.is_pii_field(key) => format!("{}={}", key, REDACTED),
+                _ => pair.to_string(),
+            })
+            .collect();
+        format!("{}?{}", uri.path(), query.join("&"))
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_pii_field(key) {
+                        *value = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.redact_json(v)),
+            _ => {}
+        }
+    }
+
+    /// Parse and redact a body; anything that isn't small JSON is dropped
+    fn redact_body(&self, bytes: &Bytes) -> Option<serde_json::Value> {
+        if bytes.is_empty() || bytes.len() > self.config.max_body_bytes {
+            return None;
+        }
+        let mut value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+        self.redact_json(&mut value);
+        Some(value)
+    }
+}
+
+/// Capture middleware - records sampled traffic when capture is enabled
+pub async fn capture_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let capture = &state.traffic_capture;
+    let path = request.uri().path();
+    if !capture.is_enabled()
+        || EXCLUDED_PREFIXES.iter().any(|p| path.starts_with(p))
+        || !capture.should_sample()
+    {
+        return next.run(request).await;
+    }
+
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let uri = capture.redact_uri(request.uri());
+    let request_headers = capture.redact_headers(request.headers());
+
+    let (parts, body) = request.into_parts();
+    let request_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let request_body = capture.redact_body(&request_bytes);
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(request_bytes)))
+        .await;
+
+    let (parts, body) = response.into_parts();
+    let response_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    capture
+        .record(CapturedExchange {
+            id: Uuid::new_v4(),
+            captured_at: Utc::now(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            method,
+            uri,
+            request_headers,
+            request_body,
+            status: parts.status.as_u16(),
+            response_headers: capture.redact_headers(&parts.headers),
+            response_body: capture.redact_body(&response_bytes),
+        })
+        .await;
+
+    Response::from_parts(parts, Body::from(response_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture() -> TrafficCapture {
+        TrafficCapture::new(CaptureConfig {
+            enabled: true,
+            sample_rate: 1.0,
+            buffer_size: 2,
+            max_body_bytes: 1024,
+            redact_fields: vec!["loyalty_number".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_redacts_nested_pii_fields() {
+        let capture = capture();
+        let body = Bytes::from(
+            r#"{"email":"a@b.c","items":[{"sku":"X","loyalty_number":"42"}],"address":{"address1":"Main St"}}"#,
+        );
+        let value = capture.redact_body(&body).unwrap();
+        assert_eq!(value["email"], REDACTED);
+        assert_eq!(value["items"][0]["sku"], "X");
+        assert_eq!(value["items"][0]["loyalty_number"], REDACTED);
+        assert_eq!(value["address"]["address1"], REDACTED);
+    }
+
+    #[test]
+    fn test_drops_non_json_and_oversized_bodies() {
+        let capture = capture();
+        assert!(capture.redact_body(&Bytes::from("email=a@b.c")).is_none());
+        assert!(capture.redact_body(&Bytes::from(vec![b' '; 2048])).is_none());
+    }
+
+    #[test]
+    fn test_redacts_headers_and_query() {
+        let capture = capture();
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer abc".parse().unwrap());
+        headers.insert("accept", "application/json".parse().unwrap());
+        let redacted = capture.redact_headers(&headers);
+        assert_eq!(redacted["authorization"], REDACTED);
+        assert_eq!(redacted["accept"], "application/json");
+
+        let uri: axum::http::Uri = "/api/v1/customers?email=a@b.c&limit=10".parse().unwrap();
+        assert_eq!(capture.redact_uri(&uri), "/api/v1/customers?email=[REDACTED]&limit=10");
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_evicts_oldest() {
+        let capture = capture();
+        for i in 0..3 {
+            capture
+                .record(CapturedExchange {
+                    id: Uuid::new_v4(),
+                    captured_at: Utc::now(),
+                    duration_ms: i,
+                    method: "GET".to_string(),
+                    uri: "/".to_string(),
+                    request_headers: BTreeMap::new(),
+                    request_body: None,
+                    status: 200,
+                    response_headers: BTreeMap::new(),
+                    response_body: None,
+                })
+                .await;
+        }
+        let snapshot = capture.snapshot().await;
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].duration_ms, 1);
+        assert_eq!(capture.clear().await, 2);
+    }
+}
@@ -0,0 +1,212 @@
+//! Conditional GET for catalog routes
+//!
+//! Successful GET responses of the product and collection routes get a
+//! strong `ETag` (a hash of the body). Handlers of single products and
+//! collections also set `Last-Modified` from `updated_at`. A request whose
+//! `If-None-Match` matches the ETag - or, without `If-None-Match`, whose
+//! `If-Modified-Since` is no older than `Last-Modified` - gets
+//! `304 Not Modified` with no body, so CDNs and the demo server can
+//! revalidate instead of downloading the payload again.
+//!
+//! This runs outside the response cache, so cached responses are
+//! validated the same way.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Routes (relative to `/api/v1`) answering conditional requests
+pub const CONDITIONAL_ROUTES: &[&str] = &[
+    "/products",
+    "/products/:id",
+    "/collections",
+    "/collections/:id_or_handle",
+    "/storefront/products",
+    "/storefront/products/:id",
+    "/storefront/products/by-slug/:slug",
+];
+
+/// Largest response body hashed for an ETag; bigger ones, and streamed
+/// bodies of unknown length, are sent as is
+const MAX_ETAG_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Response headers kept on a `304 Not Modified`
+const NOT_MODIFIED_HEADERS: [HeaderName; 5] = [
+    header::ETAG,
+    header::LAST_MODIFIED,
+    header::CACHE_CONTROL,
+    header::VARY,
+    header::EXPIRES,
+];
+
+/// `path` is the matched route template, with or without the `/api/v1` prefix
+pub fn is_conditional_route(path: &str) -> bool {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    CONDITIONAL_ROUTES.contains(&path)
+}
+
+/// HTTP date of a timestamp, e.g. `Tue, 23 Jan 2024 14:13:35 GMT`
+pub fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// `Last-Modified` header for a handler response
+pub fn last_modified(updated_at: DateTime<Utc>) -> [(HeaderName, String); 1] {
+    [(header::LAST_MODIFIED, http_date(updated_at))]
+}
+
+/// Strong ETag of a response body
+pub fn etag(body: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(&Sha256::digest(body)[..16]))
+}
+
+/// Whether `If-None-Match` lists the ETag; weak tags compare equal to strong ones
+fn none_match(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == etag)
+}
+
+/// Whether the body's length is known, from the body or `Content-Length`,
+/// and small enough to buffer and hash
+fn fits_etag_limit(headers: &HeaderMap, body: &Body) -> bool {
+    body.size_hint()
+        .upper()
+        .or_else(|| {
+            headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|len| len.parse().ok())
+        })
+        .is_some_and(|len| len <= MAX_ETAG_BODY_BYTES as u64)
+}
+
+/// Whether the client's copy is current, per RFC 9110 precedence
+fn is_not_modified(request: &HeaderMap, etag: &str, last_modified: Option<&str>) -> bool {
+    if let Some(if_none_match) = request.get(header::IF_NONE_MATCH).and_then(|h| h.to_str().ok()) {
+        return none_match(if_none_match, etag);
+    }
+    let parse = |value: &str| DateTime::parse_from_rfc2822(value).ok();
+    match (
+        request.get(header::IF_MODIFIED_SINCE).and_then(|h| h.to_str().ok()).and_then(parse),
+        last_modified.and_then(parse),
+    ) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+/// Conditional GET middleware - adds ETags and answers revalidations with 304
+pub async fn conditional_get_middleware(request: Request<Body>, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let conditional = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| is_conditional_route(path.as_str()));
+    if !conditional {
+        return next.run(request).await;
+    }
+
+    let request_headers = request.headers().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    if !fits_etag_limit(&parts.headers, &body) {
+        return Response::from_parts(parts, body);
+    }
+    let bytes = match axum::body::to_bytes(body, MAX_ETAG_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response for ETag: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let etag = etag(&bytes);
+    let last_modified = parts
+        .headers
+        .get(header::LAST_MODIFIED)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+
+    if is_not_modified(&request_headers, &etag, last_modified.as_deref()) {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        for name in NOT_MODIFIED_HEADERS {
+            if let Some(value) = parts.headers.get(&name) {
+                not_modified.headers_mut().insert(name, value.clone());
+            }
+        }
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            not_modified.headers_mut().insert(header::ETAG, value);
+        }
+        return not_modified;
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(header::ETAG, value);
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use chrono::TimeZone;
+    use tower::Service;
+
+    #[test]
+    fn test_is_not_modified() {
+        let tag = etag(br#"{"id":"abc"}"#);
+        let updated_at = Utc.with_ymd_and_hms(2024, 1, 23, 14, 13, 35).unwrap();
+        let modified = http_date(updated_at);
+        assert_eq!(modified, "Tue, 23 Jan 2024 14:13:35 GMT");
+
+        let headers = |name: HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert!(is_not_modified(&headers(header::IF_NONE_MATCH, &tag), &tag, None));
+        assert!(is_not_modified(&headers(header::IF_NONE_MATCH, &format!("\"x\", W/{}", tag)), &tag, None));
+        assert!(!is_not_modified(&headers(header::IF_NONE_MATCH, "\"x\""), &tag, Some(&modified)));
+        assert!(is_not_modified(&headers(header::IF_MODIFIED_SINCE, &modified), &tag, Some(&modified)));
+        let earlier = http_date(updated_at - chrono::Duration::seconds(1));
+        assert!(!is_not_modified(&headers(header::IF_MODIFIED_SINCE, &earlier), &tag, Some(&modified)));
+        assert!(!is_not_modified(&HeaderMap::new(), &tag, Some(&modified)));
+
+        assert!(is_conditional_route("/api/v1/products/:id"));
+        assert!(!is_conditional_route("/api/v1/orders"));
+    }
+
+    #[tokio::test]
+    async fn test_large_body_passes_through() {
+        let large = vec![b'x'; MAX_ETAG_BODY_BYTES + 1];
+        let body = large.clone();
+        let mut app = Router::new()
+            .route("/products", get(move || async move { body }))
+            .route("/products/:id", get(|| async { "{}" }))
+            .route_layer(axum::middleware::from_fn(conditional_get_middleware));
+
+        let response = app.call(axum::http::Request::get("/products").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::ETAG).is_none());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(bytes.len(), large.len());
+
+        let response = app.call(axum::http::Request::get("/products/abc").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.headers()[header::ETAG], etag(b"{}").as_str());
+    }
+}
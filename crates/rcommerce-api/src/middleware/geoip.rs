@@ -0,0 +1,222 @@
+//! IP geolocation for storefront routes
+//!
+//! Resolves every storefront request to a `GeoLocation` (country, currency,
+//! locale) and adds it to the request extensions, so handlers can default
+//! the cart currency and prefill the country for tax and shipping estimates.
+//! Requests from blocked countries are refused with 451 Unavailable For
+//! Legal Reasons. A storefront override is read from the `rc_geo` cookie.
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{header, request::Parts, Extensions, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::state::AppState;
+use rcommerce_core::repository::ip_matches;
+use rcommerce_core::services::GeoOverride;
+
+/// Cookie holding the storefront's override
+pub const GEO_OVERRIDE_COOKIE: &str = "rc_geo";
+
+/// Reverse proxies whose forwarding headers are believed
+/// (`server.trusted_proxies`), added to every request's extensions
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Arc<Vec<String>>);
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<String>) -> Self {
+        Self(Arc::new(networks))
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|network| ip_matches(network, ip))
+    }
+}
+
+/// Client IP of a request; see [`forwarded_ip`]
+pub fn client_ip(request: &Request<Body>) -> Option<IpAddr> {
+    request_ip(request.headers(), request.extensions())
+}
+
+fn request_ip(headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    let peer = extensions.get::<ConnectInfo<SocketAddr>>().map(|ci| ci.0.ip());
+    let proxies = extensions.get::<TrustedProxies>().cloned().unwrap_or_default();
+    forwarded_ip(headers, peer, &proxies)
+}
+
+/// Client IP behind the connection from `peer`
+///
+/// `X-Forwarded-For` and `X-Real-IP` are only read when the peer is a
+/// trusted proxy, since anyone else can send them. The client is then the
+/// rightmost `X-Forwarded-For` hop that is not a trusted proxy itself.
+pub fn forwarded_ip(headers: &HeaderMap, peer: Option<IpAddr>, proxies: &TrustedProxies) -> Option<IpAddr> {
+    let peer = peer?;
+    if !proxies.contains(peer) {
+        return Some(peer);
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim)
+        .collect();
+    if hops.is_empty() {
+        let real_ip = headers.get("x-real-ip").and_then(|h| h.to_str().ok());
+        return Some(real_ip.and_then(|s| s.trim().parse().ok()).unwrap_or(peer));
+    }
+
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !proxies.contains(ip) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+/// Value of a header set by the reverse proxy (such as a CDN's country
+/// header); `None` unless the request came through a trusted proxy, since
+/// clients can send it themselves
+pub fn proxy_header<'a>(request: &'a Request<Body>, name: &str) -> Option<&'a str> {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>()?.0.ip();
+    let proxies = request.extensions().get::<TrustedProxies>()?;
+    if !proxies.contains(peer) {
+        return None;
+    }
+    request.headers().get(name)?.to_str().ok()
+}
+
+/// Extractor for the client IP of a request (see [`forwarded_ip`])
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp(request_ip(&parts.headers, &parts.extensions)))
+    }
+}
+
+/// Storefront override from the `rc_geo` cookie
+pub fn geo_override(headers: &HeaderMap) -> Option<GeoOverride> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == GEO_OVERRIDE_COOKIE)
+        .and_then(|(_, value)| GeoOverride::from_cookie_value(value))
+}
+
+/// GeoIP middleware - adds `GeoLocation` to request extensions
+pub async fn geoip_middleware(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let geoip = &state.geoip;
+    let header_country = geoip
+        .country_header()
+        .and_then(|name| proxy_header(&request, name))
+        .map(str::to_string);
+    let ip = client_ip(&request);
+    let geo_override = geo_override(request.headers());
+
+    let location = geoip
+        .resolve(ip, header_country.as_deref(), geo_override.as_ref())
+        .await;
+
+    if location.blocked {
+        tracing::info!(
+            "Blocked request from {} ({})",
+            location.detected_country.as_deref().unwrap_or_default(),
+            request.uri().path()
+        );
+        return (
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+            Json(serde_json::json!({
+                "error": "This store is not available in your region",
+                "country": location.detected_country,
+            })),
+        )
+            .into_response();
+    }
+
+    request.extensions_mut().insert(location);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_via(peer: &str, forwarded_for: &str) -> Request<Body> {
+        let mut request = Request::builder()
+            .header("x-forwarded-for", forwarded_for)
+            .header(header::COOKIE, "session=abc; rc_geo=DE.EUR.de-DE")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(format!("{}:443", peer).parse::<SocketAddr>().unwrap()));
+        request.extensions_mut().insert(TrustedProxies::new(vec!["10.0.0.0/8".to_string()]));
+        request
+    }
+
+    #[test]
+    fn test_client_ip_and_override_cookie() {
+        let request = request_via("10.0.0.1", "203.0.113.7");
+        assert_eq!(client_ip(&request), Some("203.0.113.7".parse().unwrap()));
+
+        let geo_override = geo_override(request.headers()).unwrap();
+        assert_eq!(geo_override.country.as_deref(), Some("DE"));
+        assert_eq!(geo_override.locale.as_deref(), Some("de-DE"));
+    }
+
+    #[test]
+    fn test_forwarded_ip_trusts_only_proxies() {
+        // An untrusted peer can't claim another address
+        let request = request_via("198.51.100.9", "10.1.2.3");
+        assert_eq!(client_ip(&request), Some("198.51.100.9".parse().unwrap()));
+
+        // Behind trusted proxies, the rightmost untrusted hop is the client;
+        // anything left of it was sent by the client
+        let request = request_via("10.0.0.1", "10.1.2.3, 203.0.113.7, 10.0.0.2");
+        assert_eq!(client_ip(&request), Some("203.0.113.7".parse().unwrap()));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", "203.0.113.8".parse().unwrap());
+        let proxies = TrustedProxies::new(vec!["10.0.0.1".to_string()]);
+        let peer = Some("10.0.0.1".parse().unwrap());
+        assert_eq!(forwarded_ip(&headers, peer, &proxies), Some("203.0.113.8".parse().unwrap()));
+        assert_eq!(forwarded_ip(&headers, peer, &TrustedProxies::default()), peer);
+        assert_eq!(forwarded_ip(&headers, None, &proxies), None);
+    }
+
+    #[test]
+    fn test_country_header_only_from_trusted_proxies() {
+        let mut request = request_via("10.0.0.1", "203.0.113.7");
+        request.headers_mut().insert("cf-ipcountry", "DE".parse().unwrap());
+        assert_eq!(proxy_header(&request, "cf-ipcountry"), Some("DE"));
+
+        // A client connecting directly can't pick its country
+        let mut request = request_via("198.51.100.9", "203.0.113.7");
+        request.headers_mut().insert("cf-ipcountry", "DE".parse().unwrap());
+        assert_eq!(proxy_header(&request, "cf-ipcountry"), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Idempotency keys for write requests
+//!
+//! A POST with an `Idempotency-Key` header runs once per key. A retry with
+//! the same key and body gets the stored response back, marked with
+//! `Idempotent-Replayed: true`, instead of placing another order or
+//! charging again. While the first request is still running a retry gets
+//! 409; reusing a key for a different request gets 422. Server errors are
+//! not stored, so the request can be retried with the same key.
+//!
+//! Keys are scoped to the authenticated customer, so this runs after the
+//! auth middleware; requests without a key are not affected.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::jobs::spawn_singleton;
+use rcommerce_core::models::{IdempotencyOutcome, StoredResponse};
+use rcommerce_core::services::IdempotentRequest;
+use rcommerce_core::Error;
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header marking a replayed response
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Rebuild a stored response for a retry
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored.content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Idempotency middleware - runs a keyed POST once and replays its response
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let idempotency = &state.idempotency;
+    if request.method() != Method::POST || !idempotency.config().enabled {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let Ok(key) = key.to_str().map(|k| k.trim().to_string()) else {
+        return Error::validation("Idempotency-Key must be visible ASCII").into_response();
+    };
+    let Some(scope) = request.extensions().get::<JwtAuth>().map(|auth| auth.customer_id.to_string()) else {
+        tracing::debug!("Idempotency-Key ignored on unauthenticated request");
+        return next.run(request).await;
+    };
+
+    let method = request.method().to_string();
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, idempotency.config().max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return Error::HttpError(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Request body is too large to use with an Idempotency-Key".to_string(),
+            )
+            .into_response()
+        }
+    };
+
+    let idempotent = IdempotentRequest { scope: &scope, key: &key, method: &method, path: &path, body: &body };
+    let lock_id = match idempotency.begin(&idempotent, Utc::now()).await {
+        Ok(IdempotencyOutcome::Started { lock_id }) => lock_id,
+        Ok(IdempotencyOutcome::Replay(stored)) => {
+            tracing::info!("Replaying response for idempotency key '{}' ({} {})", key, method, path);
+            return replay(stored);
+        }
+        Ok(IdempotencyOutcome::InProgress) => {
+            return Error::HttpError(
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still being processed".to_string(),
+            )
+            .into_response()
+        }
+        Ok(IdempotencyOutcome::Mismatch) => {
+            return Error::HttpError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used for a different request".to_string(),
+            )
+            .into_response()
+        }
+        Err(e) => return e.into_response(),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let (parts, body) = response.into_parts();
+    let response_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response for idempotency key '{}': {}", key, e);
+            if let Err(e) = idempotency.release(&scope, &key, lock_id).await {
+                tracing::error!("Failed to release idempotency key '{}': {}", key, e);
+            }
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string),
+        body: response_bytes.to_vec(),
+    };
+    if let Err(e) = idempotency.complete(&scope, &key, lock_id, &stored).await {
+        tracing::error!("Failed to store response for idempotency key '{}': {}", key, e);
+    }
+
+    Response::from_parts(parts, Body::from(response_bytes))
+}
+
+/// Spawn the periodic purge of expired idempotency keys, on the instance
+/// leading `idempotency_purge`
+pub fn spawn_purge(state: &AppState) {
+    let idempotency = state.idempotency.clone();
+    if !idempotency.config().enabled {
+        return;
+    }
+    let interval = std::time::Duration::from_secs(idempotency.config().purge_interval_secs.max(60));
+    spawn_singleton(state.locks.clone(), "idempotency_purge", interval, move || {
+        let idempotency = idempotency.clone();
+        async move {
+            match idempotency.purge_expired(Utc::now()).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} expired idempotency keys", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Idempotency key purge failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay() {
+        let response = replay(StoredResponse {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"id":"1"}"#.to_vec(),
+        });
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"id":"1"}"#);
+    }
+}
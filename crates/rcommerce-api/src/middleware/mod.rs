@@ -2,13 +2,12 @@
 
 use axum::{
     body::Body,
-    extract::{ConnectInfo, Request, State},
+    extract::{MatchedPath, Request, State},
     http::{HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use std::collections::HashMap;
-use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
@@ -16,9 +15,21 @@ use tokio::sync::Mutex;
 use crate::state::AppState;
 use rcommerce_core::config::TlsConfig;
 use rcommerce_core::services::AuthService;
+use rcommerce_core::Error;
 
 pub mod scopes;
+pub mod access_control;
+pub mod access_log;
 pub mod api_key_auth;
+pub mod audit;
+pub mod capture;
+pub mod conditional;
+pub mod geoip;
+pub mod idempotency;
+pub mod request_stats;
+pub mod response_cache;
+pub mod session;
+pub mod storefront_key;
 
 pub use api_key_auth::{
     ApiKeyAuth, 
@@ -27,6 +38,15 @@ pub use api_key_auth::{
     api_key_auth_middleware, 
     combined_auth_middleware
 };
+pub use access_log::access_log_middleware;
+pub use audit::audit_middleware;
+pub use capture::{capture_middleware, CapturedExchange, TrafficCapture};
+pub use conditional::conditional_get_middleware;
+pub use geoip::geoip_middleware;
+pub use idempotency::idempotency_middleware;
+pub use request_stats::request_stats_middleware;
+pub use response_cache::response_cache_middleware;
+pub use storefront_key::storefront_key_middleware;
 
 /// Rate limiter for auth endpoints (in-memory, per-IP)
 #[derive(Clone)]
@@ -50,6 +70,11 @@ impl AuthRateLimiter {
 
     /// Check if the request is allowed and increment counter
     pub async fn check_and_increment(&self, ip: &str) -> bool {
+        self.check_and_increment_with_limit(ip, self.max_attempts).await
+    }
+
+    /// Like `check_and_increment`, with a per-key attempt limit
+    pub async fn check_and_increment_with_limit(&self, ip: &str, max_attempts: u32) -> bool {
         let mut store = self.store.lock().await;
         let now = Instant::now();
         let window = Duration::from_secs(self.window_secs);
@@ -62,7 +87,7 @@ impl AuthRateLimiter {
                     *attempts = 1;
                     *first_attempt = now;
                     true
-                } else if *attempts < self.max_attempts {
+                } else if *attempts < max_attempts {
                     // Increment attempts
                     *attempts += 1;
                     true
@@ -100,21 +125,10 @@ pub async fn auth_rate_limit_middleware(
         .cloned()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Extract connection info from request extensions
-    let addr = request
-        .extensions()
-        .get::<ConnectInfo<SocketAddr>>()
-        .map(|ci| ci.0)
-        .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
-
-    // Get client IP (use X-Forwarded-For if behind proxy, otherwise use socket addr)
-    let ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.split(',').next())
-        .map(|s| s.trim().to_string())
-        .unwrap_or_else(|| addr.ip().to_string());
+    // Client IP (forwarding headers only count from trusted proxies)
+    let ip = geoip::client_ip(&request)
+        .unwrap_or(std::net::IpAddr::from([0, 0, 0, 0]))
+        .to_string();
 
     // Check rate limit (5 attempts per minute)
     if !state.auth_rate_limiter.check_and_increment(&ip).await {
@@ -149,32 +163,45 @@ pub async fn auth_middleware(
             })?
         }
         None => {
-            tracing::warn!("No Authorization header found");
+            // Browser storefronts may use a session cookie instead
+            if let Some(auth) = session::session_auth(&state, request.method(), request.headers()).await? {
+                tracing::debug!("Session verified for customer: {}", auth.customer_id);
+                request.extensions_mut().insert(auth);
+                return Ok(next.run(request).await);
+            }
+            tracing::warn!("No Authorization header or session found");
             return Err(StatusCode::UNAUTHORIZED);
         }
     };
 
     tracing::debug!("Extracted bearer token");
 
-    // Verify token
-    match state.auth_service.verify_token(token) {
-        Ok(claims) => {
-            tracing::debug!("Token verified for customer: {}", claims.sub);
-            
-            // Create JWT auth context and add to request extensions
-            let auth = JwtAuth {
-                customer_id: claims.sub,
-                email: claims.email,
-                permissions: claims.permissions,
-            };
-            
-            request.extensions_mut().insert(auth);
-            
-            Ok(next.run(request).await)
+    // Verify token (and that its login session hasn't been revoked)
+    let claims = state.verify_access_token(token).await.map_err(token_rejection)?;
+    tracing::debug!("Token verified for customer: {}", claims.sub);
+
+    // Create JWT auth context and add to request extensions
+    let auth = JwtAuth {
+        customer_id: claims.sub,
+        email: claims.email,
+        permissions: claims.permissions,
+    };
+
+    request.extensions_mut().insert(auth);
+
+    Ok(next.run(request).await)
+}
+
+/// Status for a bearer token `AppState::verify_access_token` refused
+pub(crate) fn token_rejection(error: Error) -> StatusCode {
+    match error {
+        Error::Unauthorized(reason) => {
+            tracing::warn!("Token verification failed: {}", reason);
+            StatusCode::UNAUTHORIZED
         }
-        Err(e) => {
-            tracing::warn!("Token verification failed: {}", e);
-            Err(StatusCode::UNAUTHORIZED)
+        e => {
+            tracing::error!("Failed to verify access token: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
         }
     }
 }
@@ -200,12 +227,25 @@ pub async fn rate_limit_middleware(
     Ok(next.run(request).await)
 }
 
-/// Admin-only middleware
+/// Admin route middleware
+///
+/// Checks the caller's current permissions (base role plus assigned staff
+/// roles) against the permission the route needs (see
+/// `scopes::admin_route_permission`). Adds JwtAuth with those permissions to
+/// request extensions so handlers can record the acting staff member.
 pub async fn admin_middleware(
     State(state): State<AppState>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
+    // Staff connections must present a verified certificate when configured
+    if state.admin_requires_client_cert {
+        if let Some(denial) = access_control::client_certificate_denial(&request) {
+            access_control::record_denial(&state.access_denials, denial);
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
     // Get Authorization header
     let auth_header = request
         .headers()
@@ -224,16 +264,58 @@ pub async fn admin_middleware(
     };
 
     // Verify token
-    let claims = state
-        .auth_service
-        .verify_token(token)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let claims = state.verify_access_token(token).await.map_err(token_rejection)?;
 
-    // Check for admin permission
-    if !claims.permissions.contains(&"admin".to_string()) {
+    // Look permissions up rather than trusting the token, so role changes apply at once
+    let permissions = match state.roles.effective_permissions(claims.sub).await {
+        Ok(Some(effective)) => effective.permissions,
+        Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            tracing::error!("Failed to load permissions for {}: {}", claims.sub, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    if !scopes::allows_admin_route(&permissions, request.method(), &path) {
+        tracing::warn!(
+            "Permission denied: customer {} tried to {} {}",
+            claims.sub,
+            request.method(),
+            path
+        );
         return Err(StatusCode::FORBIDDEN);
     }
 
+    // Staff must have logged in with a second factor when the policy requires it
+    if state.two_factor.config().required_for_admins {
+        match state.two_factor.admin_access_allowed(claims.sub, claims.iat).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("Admin access refused to customer {}: two-factor login required", claims.sub);
+                return Ok(Error::HttpError(
+                    StatusCode::FORBIDDEN,
+                    "Two-factor authentication is required for admin access; enable it at /auth/2fa/enroll and log in again".to_string(),
+                )
+                .into_response());
+            }
+            Err(e) => {
+                tracing::error!("Failed to check two-factor status of {}: {}", claims.sub, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    request.extensions_mut().insert(JwtAuth {
+        customer_id: claims.sub,
+        email: claims.email,
+        permissions,
+    });
+
     Ok(next.run(request).await)
 }
 
@@ -0,0 +1,45 @@
+//! Request and 5xx counts for the `anomaly_detection` job
+//!
+//! Every response is counted in memory and the counts are added to the
+//! shared `request_stats` table once a minute, so the 5xx rate covers all
+//! instances.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+/// How often an instance adds its counts to `request_stats`
+const FLUSH_INTERVAL_SECS: u64 = 60;
+
+/// Count the response's status
+pub async fn request_stats_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    state.metrics.record_response(response.status().as_u16());
+    response
+}
+
+/// Spawn the periodic flush of request counts
+pub fn spawn_flush(state: &AppState) {
+    let metrics = state.metrics.clone();
+    if !metrics.config().anomalies.enabled {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(FLUSH_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = metrics.flush_request_stats().await {
+                tracing::error!("Failed to save request stats: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,182 @@
+//! Per-route response caching
+//!
+//! GET responses of routes with a `[cache.responses]` rule get the rule's
+//! `Cache-Control` and `Surrogate-Key` headers. Successful `public`
+//! responses are kept in memory and served with `X-Cache: HIT` and an
+//! `Age` header until they expire or are purged. Staff allowed to purge the
+//! cache (`settings:write`) skip it by sending the bypass header; their
+//! response replaces the cached one. Streamed responses pass through
+//! uncached.
+//!
+//! This runs after the auth and storefront key middleware, so cached
+//! responses are only served to callers who may see them.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::middleware::scopes;
+use crate::state::AppState;
+use rcommerce_core::cache::{CachePolicy, CachedResponse, ResponseCache};
+use rcommerce_core::services::AuthService;
+
+/// Header telling whether a response came from the response cache
+pub const X_CACHE_HEADER: &str = "x-cache";
+
+/// Header listing a response's surrogate keys
+pub const SURROGATE_KEY_HEADER: &str = "surrogate-key";
+
+/// Largest response body kept in the response cache
+const MAX_CACHED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Rebuild a cached response
+fn cached_response(cached: CachedResponse, policy: &CachePolicy) -> Response {
+    let status = StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK);
+    let age = cached.age_secs();
+    let mut response = (status, cached.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = cached.content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(header::AGE, HeaderValue::from(age));
+    set_policy_headers(&mut response, policy, "HIT");
+    response
+}
+
+/// Add the policy's caching headers to a response
+fn set_policy_headers(response: &mut Response, policy: &CachePolicy, cache_status: &'static str) {
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&policy.cache_control()) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    if let Some(value) = policy.surrogate_key_header().and_then(|keys| HeaderValue::from_str(&keys).ok()) {
+        headers.insert(SURROGATE_KEY_HEADER, value);
+    }
+    headers.insert(X_CACHE_HEADER, HeaderValue::from_static(cache_status));
+}
+
+/// Whether the request asks to bypass the cache and comes from staff who may purge it
+async fn is_staff_bypass(state: &AppState, headers: &HeaderMap) -> bool {
+    if !headers.contains_key(state.response_cache.bypass_header()) {
+        return false;
+    }
+    let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(AuthService::extract_bearer_token)
+    else {
+        return false;
+    };
+    let Ok(claims) = state.verify_access_token(token).await else {
+        return false;
+    };
+    match state.roles.effective_permissions(claims.sub).await {
+        Ok(Some(effective)) => {
+            scopes::allows_admin_route(&effective.permissions, &Method::POST, "/admin/cache/purge")
+        }
+        Ok(None) => false,
+        Err(e) => {
+            tracing::error!("Failed to load permissions for {}: {}", claims.sub, e);
+            false
+        }
+    }
+}
+
+/// Response cache middleware - applies the route's cache policy
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    // Nested routers see the path without `/api/v1`; rules use the full path
+    let uri = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|original| original.0.clone())
+        .unwrap_or_else(|| request.uri().clone());
+    let Some(policy) = state.response_cache.policy_for(uri.path()) else {
+        return next.run(request).await;
+    };
+
+    let path_and_query = uri.path_and_query().map(|p| p.as_str()).unwrap_or(uri.path());
+    let key = ResponseCache::key(request.method().as_str(), path_and_query);
+    let bypass = policy.is_shared() && is_staff_bypass(&state, request.headers()).await;
+    if policy.is_shared() && !bypass {
+        if let Some(cached) = state.response_cache.get(&key) {
+            return cached_response(cached, &policy);
+        }
+    }
+
+    let response = next.run(request).await;
+    let cache_status = if bypass { "BYPASS" } else { "MISS" };
+    // Streamed bodies have no known length and are never buffered
+    let streamed = response.body().size_hint().upper().is_none();
+    if !policy.is_shared() || response.status() != StatusCode::OK || streamed {
+        let mut response = response;
+        set_policy_headers(&mut response, &policy, cache_status);
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_CACHED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response for caching {}: {}", key, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let content_type = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+    state
+        .response_cache
+        .insert(key, uri.path(), &policy, parts.status.as_u16(), content_type, bytes.to_vec());
+
+    let mut response = Response::from_parts(parts, Body::from(bytes));
+    set_policy_headers(&mut response, &policy, cache_status);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcommerce_core::config::CacheVisibility;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_cached_response() {
+        let policy = CachePolicy {
+            ttl_secs: 300,
+            visibility: CacheVisibility::Public,
+            surrogate_keys: vec!["products".to_string(), "product:abc".to_string()],
+        };
+        let response = cached_response(
+            CachedResponse {
+                status: 200,
+                content_type: Some("application/json".to_string()),
+                body: br#"{"id":"abc"}"#.to_vec(),
+                surrogate_keys: policy.surrogate_keys.clone(),
+                path: "/api/v1/products/abc".to_string(),
+                stored_at: Instant::now() - Duration::from_secs(5),
+                expires_at: Instant::now() + Duration::from_secs(295),
+            },
+            &policy,
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=300");
+        assert_eq!(response.headers()[SURROGATE_KEY_HEADER], "products product:abc");
+        assert_eq!(response.headers()[X_CACHE_HEADER], "HIT");
+        assert_eq!(response.headers()[header::AGE], "5");
+    }
+}
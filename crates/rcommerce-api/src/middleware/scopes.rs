@@ -6,12 +6,13 @@
 use axum::{
     body::Body,
     extract::Request,
-    http::StatusCode,
+    http::{Method, StatusCode},
     middleware::Next,
     response::Response,
 };
 use std::sync::Arc;
 
+use rcommerce_core::models::AdminRouteAccess;
 use rcommerce_core::services::{AuthService, ScopeChecker, Resource, Action};
 
 /// Extract authentication info from request and check scope permissions
@@ -76,13 +77,7 @@ pub async fn require_scope_middleware(
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Verify token and get claims
-    let claims = match state.auth_service.verify_token(token) {
-        Ok(claims) => claims,
-        Err(e) => {
-            tracing::warn!("Token verification failed: {}", e);
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    };
+    let claims = state.verify_access_token(token).await.map_err(super::token_rejection)?;
 
     // Check permissions using ScopeChecker
     let scope_checker = ScopeChecker::new(&claims.permissions)
@@ -132,11 +127,184 @@ pub fn require_admin() -> impl Fn(Request<Body>, Next) -> MiddlewareFn {
     }
 }
 
+/// Admin route prefixes (relative to `/api/v1`) and the resource each
+/// manages. Staff need `resource:read` for GET and `resource:write`
+/// otherwise; changing roles needs `users:admin`. Routes not listed here
+/// need the global `admin` permission.
+const ADMIN_ROUTE_RESOURCES: &[(&str, Resource)] = &[
+    ("/admin/roles", Resource::Users),
+    ("/admin/permissions", Resource::Users),
+    ("/admin/customers/:id/roles", Resource::Users),
+    ("/admin/customers/:id/permissions", Resource::Users),
+    ("/admin/customers/:id/2fa", Resource::Users),
+    ("/admin/customers/:id/customer-group", Resource::Customers),
+    ("/admin/customers/:id/shipping-preference", Resource::Customers),
+    ("/admin/customers/deleted", Resource::Customers),
+    ("/admin/customers/:id", Resource::Customers),
+    ("/admin/customer-groups", Resource::Customers),
+    ("/admin/in-app-notifications", Resource::Customers),
+    ("/admin/orders", Resource::Orders),
+    ("/admin/returns", Resource::Orders),
+    ("/admin/backorders", Resource::Orders),
+    ("/admin/shipments", Resource::Orders),
+    ("/admin/shipping", Resource::Orders),
+    ("/admin/pickup", Resource::Inventory),
+    ("/admin/inventory", Resource::Inventory),
+    ("/admin/print-batches", Resource::Orders),
+    ("/admin/printing", Resource::Orders),
+    ("/admin/gift-cards", Resource::Payments),
+    ("/admin/payments", Resource::Payments),
+    ("/admin/products", Resource::Products),
+    ("/admin/categories", Resource::Products),
+    ("/admin/collections", Resource::Products),
+    ("/admin/attributes", Resource::Products),
+    ("/admin/price-lists", Resource::Products),
+    ("/admin/addons", Resource::Products),
+    ("/admin/marketplaces", Resource::Products),
+    ("/admin/flash-sales", Resource::Products),
+    ("/admin/purchase-limits", Resource::Products),
+    ("/admin/catalog", Resource::Products),
+    ("/admin/redirects", Resource::Products),
+    ("/admin/allocation-lists", Resource::Products),
+    ("/admin/subscription-plans", Resource::Products),
+    ("/admin/suppliers", Resource::Inventory),
+    ("/admin/purchase-orders", Resource::Inventory),
+    ("/admin/purchase-order-discrepancies", Resource::Inventory),
+    ("/admin/dropship-orders", Resource::Inventory),
+    // Variant management lives under the storefront product paths
+    ("/products", Resource::Products),
+    ("/admin/statistics", Resource::Reports),
+    ("/admin/reports", Resource::Reports),
+    ("/admin/analytics", Resource::Reports),
+    ("/admin/sales", Resource::Reports),
+    ("/admin/hosted-checkouts", Resource::Orders),
+    ("/admin/fraud", Resource::Orders),
+    ("/admin/webhooks", Resource::Webhooks),
+    ("/admin/events", Resource::Webhooks),
+    ("/admin/exchange-rates", Resource::Settings),
+    ("/admin/notification-templates", Resource::Settings),
+    ("/admin/cache", Resource::Settings),
+    ("/admin/capture", Resource::Settings),
+    ("/admin/partitions", Resource::Settings),
+    ("/admin/access-denials", Resource::Settings),
+    ("/admin/automation", Resource::Settings),
+    ("/admin/incidents", Resource::Settings),
+    ("/admin/jobs", Resource::Settings),
+    ("/export", Resource::Exports),
+];
+
+/// The permission an admin route needs, or None if it needs global `admin`
+///
+/// `path` is the matched route template, with or without the `/api/v1` prefix.
+pub fn admin_route_permission(method: &Method, path: &str) -> Option<(Resource, Action)> {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    let (_, resource) = ADMIN_ROUTE_RESOURCES.iter().find(|(prefix, _)| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })?;
+
+    let action = match *method {
+        Method::GET | Method::HEAD => Action::Read,
+        _ if *resource == Resource::Users => Action::Admin,
+        _ => Action::Write,
+    };
+    Some((*resource, action))
+}
+
+/// Whether staff with `permissions` may call an admin route
+pub fn allows_admin_route(permissions: &[String], method: &Method, path: &str) -> bool {
+    if permissions.iter().any(|p| p == "admin") {
+        return true;
+    }
+    let Some((resource, action)) = admin_route_permission(method, path) else {
+        return false;
+    };
+    ScopeChecker::new(permissions)
+        .map(|checker| checker.can_staff(resource, action))
+        .unwrap_or(false)
+}
+
+/// Which admin route groups staff with `permissions` may read and change
+pub fn admin_route_access(permissions: &[String]) -> Vec<AdminRouteAccess> {
+    ADMIN_ROUTE_RESOURCES
+        .iter()
+        .map(|(path, resource)| AdminRouteAccess {
+            path: path.to_string(),
+            resource: resource.as_str().to_string(),
+            read: allows_admin_route(permissions, &Method::GET, path),
+            write: allows_admin_route(permissions, &Method::POST, path),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    
-    
-    
+    use super::*;
+
+    fn permissions(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_admin_route_permission() {
+        assert_eq!(
+            admin_route_permission(&Method::GET, "/api/v1/admin/returns"),
+            Some((Resource::Orders, Action::Read))
+        );
+        assert_eq!(
+            admin_route_permission(&Method::POST, "/admin/returns/:id/approve"),
+            Some((Resource::Orders, Action::Write))
+        );
+        assert_eq!(
+            admin_route_permission(&Method::POST, "/api/v1/admin/customers/:id/roles"),
+            Some((Resource::Users, Action::Admin))
+        );
+        assert_eq!(
+            admin_route_permission(&Method::PUT, "/api/v1/products/:id/variants/:variant_id"),
+            Some((Resource::Products, Action::Write))
+        );
+        assert_eq!(
+            admin_route_permission(&Method::GET, "/api/v1/export/:entity"),
+            Some((Resource::Exports, Action::Read))
+        );
+        // Prefixes match whole segments only
+        assert_eq!(admin_route_permission(&Method::GET, "/admin/orders-export"), None);
+        assert_eq!(admin_route_permission(&Method::GET, "/api/v1/admin/admin/api-keys"), None);
+    }
+
+    #[test]
+    fn test_allows_admin_route() {
+        let support = permissions(&["read", "orders:write", "customers:write"]);
+        assert!(allows_admin_route(&support, &Method::POST, "/api/v1/admin/returns/:id/receive"));
+        assert!(!allows_admin_route(&support, &Method::GET, "/api/v1/admin/statistics/sales"));
+        assert!(!allows_admin_route(&support, &Method::GET, "/api/v1/admin/admin/api-keys"));
+        assert!(allows_admin_route(&support, &Method::GET, "/api/v1/admin/orders/:id"));
+        assert!(!allows_admin_route(&support, &Method::POST, "/api/v1/admin/payments/:id/refund"));
+
+        // The global read/write every account carries is not a staff permission
+        let manager = permissions(&["read", "write"]);
+        assert!(!allows_admin_route(&manager, &Method::GET, "/api/v1/admin/orders/archive"));
+
+        let users = permissions(&["users:write"]);
+        assert!(allows_admin_route(&users, &Method::GET, "/api/v1/admin/roles"));
+        assert!(!allows_admin_route(&users, &Method::POST, "/api/v1/admin/roles"));
+
+        let admin = permissions(&["admin"]);
+        assert!(allows_admin_route(&admin, &Method::GET, "/api/v1/admin/admin/api-keys"));
+    }
+
+    #[test]
+    fn test_admin_route_access() {
+        let access = admin_route_access(&permissions(&["orders:read", "users:write"]));
+        assert_eq!(access.len(), ADMIN_ROUTE_RESOURCES.len());
+        let find = |path: &str| access.iter().find(|a| a.path == path).unwrap();
+        assert!(find("/admin/returns").read);
+        assert!(!find("/admin/returns").write);
+        // Changing roles needs users:admin
+        assert!(find("/admin/roles").read);
+        assert!(!find("/admin/roles").write);
+        assert!(!find("/admin/webhooks").read);
+    }
 
     #[test]
     fn test_is_api_key_detection() {
@@ -0,0 +1,186 @@
+//! Cookie sessions for storefronts
+//!
+//! With `[sessions] enabled`, a request without an `Authorization` header
+//! may authenticate with the session cookie set by `POST
+//! /api/v1/auth/session`. Because browsers attach cookies to cross-site
+//! requests, every unsafe request (anything but GET, HEAD and OPTIONS)
+//! must also send the session's CSRF token in the CSRF header; the token
+//! is readable from the CSRF cookie or `GET /api/v1/auth/session`.
+
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::cache::AuthSession;
+use rcommerce_core::config::SessionConfig;
+
+/// Value of cookie `name` in the request's `Cookie` headers
+pub fn cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Whether a request with this method must carry the CSRF token
+pub fn requires_csrf(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Whether the request's CSRF header matches the session
+pub fn csrf_valid(headers: &HeaderMap, config: &SessionConfig, session: &AuthSession) -> bool {
+    headers
+        .get(config.csrf_header.as_str())
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|token| session.csrf_matches(token.trim()))
+}
+
+fn build_cookie(config: &SessionConfig, name: &str, value: &str, max_age: u64, http_only: bool) -> HeaderValue {
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; SameSite={}",
+        name,
+        value,
+        max_age,
+        config.same_site.as_str()
+    );
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    if config.secure {
+        cookie.push_str("; Secure");
+    }
+    if let Some(ref domain) = config.domain {
+        cookie.push_str(&format!("; Domain={}", domain));
+    }
+    // Tokens are hex and names come from config, so this cannot fail in practice
+    HeaderValue::from_str(&cookie).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+/// `Set-Cookie` values for a new session: the httpOnly session cookie and
+/// the readable CSRF cookie
+pub fn session_cookies(config: &SessionConfig, token: &str, session: &AuthSession) -> [(header::HeaderName, HeaderValue); 2] {
+    let max_age = config.max_age_secs;
+    [
+        (header::SET_COOKIE, build_cookie(config, &config.cookie_name, token, max_age, true)),
+        (header::SET_COOKIE, build_cookie(config, &config.csrf_cookie_name, &session.csrf_token, max_age, false)),
+    ]
+}
+
+/// `Set-Cookie` values that remove both cookies
+pub fn clear_cookies(config: &SessionConfig) -> [(header::HeaderName, HeaderValue); 2] {
+    [
+        (header::SET_COOKIE, build_cookie(config, &config.cookie_name, "", 0, true)),
+        (header::SET_COOKIE, build_cookie(config, &config.csrf_cookie_name, "", 0, false)),
+    ]
+}
+
+/// Load the session named by the request's cookie
+///
+/// Returns the cookie token with the session, `Ok(None)` without a (live)
+/// session, and 403 when an unsafe request lacks the CSRF token.
+pub async fn request_session(
+    state: &AppState,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Result<Option<(String, AuthSession)>, StatusCode> {
+    let Some(ref store) = state.sessions else {
+        return Ok(None);
+    };
+    let Some(token) = cookie(headers, &store.config().cookie_name) else {
+        return Ok(None);
+    };
+
+    let session = match store.load(&token).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            tracing::error!("Failed to load session: {}", e);
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    };
+
+    if requires_csrf(method) && !csrf_valid(headers, store.config(), &session) {
+        tracing::warn!("CSRF check failed: customer {} {}", session.customer_id, method);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(Some((token, session)))
+}
+
+/// Authenticate a request by its session cookie
+pub async fn session_auth(state: &AppState, method: &Method, headers: &HeaderMap) -> Result<Option<JwtAuth>, StatusCode> {
+    Ok(request_session(state, method, headers).await?.map(|(_, session)| JwtAuth {
+        customer_id: session.customer_id,
+        email: session.email,
+        permissions: session.permissions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcommerce_core::config::SameSite;
+
+    fn session() -> AuthSession {
+        AuthSession {
+            customer_id: uuid::Uuid::new_v4(),
+            email: "a@example.com".to_string(),
+            permissions: Vec::new(),
+            csrf_token: "c5rf".to_string(),
+            created_at: 0,
+            expires_at: i64::MAX,
+        }
+    }
+
+    #[test]
+    fn test_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.append(header::COOKIE, HeaderValue::from_static("theme=dark; rcommerce_session=abc"));
+        headers.append(header::COOKIE, HeaderValue::from_static("rcommerce_csrf=def"));
+        assert_eq!(cookie(&headers, "rcommerce_session").as_deref(), Some("abc"));
+        assert_eq!(cookie(&headers, "rcommerce_csrf").as_deref(), Some("def"));
+        assert_eq!(cookie(&headers, "missing"), None);
+    }
+
+    #[test]
+    fn test_csrf() {
+        let config = SessionConfig::default();
+        assert!(!requires_csrf(&Method::GET));
+        assert!(requires_csrf(&Method::POST));
+        assert!(requires_csrf(&Method::DELETE));
+
+        let mut headers = HeaderMap::new();
+        assert!(!csrf_valid(&headers, &config, &session()));
+        headers.insert("x-csrf-token", HeaderValue::from_static("wrong"));
+        assert!(!csrf_valid(&headers, &config, &session()));
+        headers.insert("x-csrf-token", HeaderValue::from_static("c5rf"));
+        assert!(csrf_valid(&headers, &config, &session()));
+    }
+
+    #[test]
+    fn test_session_cookies() {
+        let config = SessionConfig {
+            same_site: SameSite::Strict,
+            domain: Some("shop.example.com".to_string()),
+            ..SessionConfig::default()
+        };
+
+        let [(_, session_cookie), (_, csrf_cookie)] = session_cookies(&config, "tok", &session());
+        let session_cookie = session_cookie.to_str().unwrap();
+        assert!(session_cookie.starts_with("rcommerce_session=tok; Path=/;"));
+        assert!(session_cookie.contains("HttpOnly"));
+        assert!(session_cookie.contains("Secure"));
+        assert!(session_cookie.contains("SameSite=Strict"));
+        assert!(session_cookie.contains("Domain=shop.example.com"));
+        // Storefront JavaScript reads the CSRF cookie
+        assert!(csrf_cookie.to_str().unwrap().starts_with("rcommerce_csrf=c5rf;"));
+        assert!(!csrf_cookie.to_str().unwrap().contains("HttpOnly"));
+
+        let [(_, cleared), _] = clear_cookies(&config);
+        assert!(cleared.to_str().unwrap().contains("Max-Age=0"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Publishable key authentication for storefront routes
+//!
+//! Storefront JavaScript sends a publishable key in the `X-Storefront-Key`
+//! header, or as `?key=` where a custom header would force a CORS
+//! preflight. The key must be publishable (secret keys are refused so they
+//! never get embedded in a page), and the request's `Origin` (or `Referer`)
+//! must match one of the key's allowed origins. Each key has its own
+//! per-minute budget, separate from the per-IP limits.
+//!
+//! Origin checks only stop other sites from using a key in a browser; the
+//! key itself is public, which is why these routes are read-only. Browsers
+//! still need the origin in the `[cors]` allowed origins.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::middleware::ApiKeyAuth;
+use crate::state::AppState;
+use rcommerce_core::repository::ApiKeyRepository;
+
+/// Header carrying the publishable key
+pub const STOREFRONT_KEY_HEADER: &str = "x-storefront-key";
+
+/// Publishable key from the `X-Storefront-Key` header or the `key` query parameter
+pub fn storefront_key(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get(STOREFRONT_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .or_else(|| {
+            request
+                .uri()
+                .query()?
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| *name == "key")
+                .map(|(_, value)| value.to_string())
+        })
+        .filter(|key| !key.is_empty())
+}
+
+/// Origin of the calling page: the `Origin` header, or the scheme and
+/// authority of the `Referer`
+pub fn request_origin(headers: &HeaderMap) -> Option<String> {
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|h| h.to_str().ok()) {
+        return Some(origin.to_string());
+    }
+    let referer = headers.get(header::REFERER).and_then(|h| h.to_str().ok())?;
+    let (scheme, rest) = referer.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    Some(format!("{}://{}", scheme, authority))
+}
+
+/// Storefront key middleware - adds the key's ApiKeyAuth to request extensions
+pub async fn storefront_key_middleware(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(key) = storefront_key(&request) else {
+        tracing::debug!("Storefront key: no key in request");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let record = match state.api_key_repository.verify_key(&key).await {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            tracing::warn!("Storefront key: invalid, revoked or expired key");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Err(e) => {
+            tracing::error!("Storefront key: Database error: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    if !record.is_publishable() {
+        tracing::warn!(
+            "Storefront key: secret key '{}' used on a storefront route; it should be rotated",
+            record.key_prefix
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let Some(denial) = crate::middleware::access_control::api_key_ip_denial(&request, &record) {
+        crate::middleware::access_control::record_denial(&state.access_denials, denial);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let origin = request_origin(request.headers());
+    if !origin.as_deref().is_some_and(|origin| record.allows_origin(origin)) {
+        tracing::warn!(
+            "Storefront key: key '{}' used from disallowed origin {:?}",
+            record.key_prefix,
+            origin
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let Some(limiter) = &state.storefront_rate_limiter {
+        let allowed = match record.rate_limit_per_minute {
+            Some(limit) if limit > 0 => {
+                limiter.check_and_increment_with_limit(&record.key_prefix, limit as u32).await
+            }
+            _ => limiter.check_and_increment(&record.key_prefix).await,
+        };
+        if !allowed {
+            tracing::warn!("Storefront key: rate limit exceeded for key '{}'", record.key_prefix);
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+    }
+
+    // Update last used timestamp (fire and forget)
+    let repo = state.api_key_repository.clone();
+    let key_id = record.id;
+    let ip = crate::middleware::geoip::client_ip(&request).map(|ip| ip.to_string());
+    tokio::spawn(async move {
+        if let Err(e) = repo.update_last_used(key_id, ip.as_deref()).await {
+            tracing::warn!("Failed to update API key last_used: {}", e);
+        }
+    });
+
+    request.extensions_mut().insert(ApiKeyAuth {
+        key_id: record.id,
+        customer_id: record.customer_id,
+        scopes: record.scopes,
+        name: record.name,
+    });
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storefront_key() {
+        let request = Request::builder()
+            .uri("/api/v1/storefront/products")
+            .header(STOREFRONT_KEY_HEADER, "pk_abc.def")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(storefront_key(&request), Some("pk_abc.def".to_string()));
+
+        let request = Request::builder()
+            .uri("/api/v1/storefront/products/1/price?currency=EUR&key=pk_abc.def")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(storefront_key(&request), Some("pk_abc.def".to_string()));
+
+        let request = Request::builder()
+            .uri("/api/v1/storefront/products?key=")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(storefront_key(&request), None);
+    }
+
+    #[test]
+    fn test_request_origin() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_origin(&headers), None);
+
+        headers.insert(header::REFERER, "https://shop.example.com:8443/cart?x=1".parse().unwrap());
+        assert_eq!(request_origin(&headers), Some("https://shop.example.com:8443".to_string()));
+
+        headers.insert(header::ORIGIN, "https://eu.example.com".parse().unwrap());
+        assert_eq!(request_origin(&headers), Some("https://eu.example.com".to_string()));
+    }
+}
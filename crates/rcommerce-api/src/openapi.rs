@@ -0,0 +1,186 @@
+//! OpenAPI document of the storefront API
+//!
+//! Generated from the route handlers' `#[utoipa::path]` annotations and the
+//! serde models they take and return, so the schemas change with the code.
+//! Served at `/api/openapi.json` (with Swagger UI at `/api/docs`) and
+//! written by `rcommerce openapi` for client generators such as
+//! openapi-typescript.
+
+use serde::Serialize;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::routes;
+
+/// Error body of failed requests (`rcommerce_core::Error`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+/// What went wrong
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorDetail {
+    pub message: String,
+    /// HTTP status code
+    pub code: u16,
+    /// e.g. `validation`, `not_found`, `authentication`
+    pub category: String,
+}
+
+/// Error body of failed checkout requests
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CheckoutError {
+    pub error: String,
+}
+
+/// Storefront API document
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "R Commerce API",
+        description = "Storefront API: catalog, carts, checkout and customer authentication"
+    ),
+    servers((url = "/api/v1")),
+    paths(
+        routes::product::list_products,
+        routes::product::get_product,
+        routes::category::category_tree,
+        routes::category::get_active_category,
+        routes::collection::list_published_collections,
+        routes::collection::get_published_collection,
+        routes::cart::create_guest_cart,
+        routes::cart::get_customer_cart,
+        routes::cart::get_cart,
+        routes::cart::delete_cart,
+        routes::cart::add_item_to_cart,
+        routes::cart::clear_cart,
+        routes::cart::update_cart_item,
+        routes::cart::remove_cart_item,
+        routes::cart::merge_carts,
+        routes::cart::apply_coupon,
+        routes::cart::remove_coupon,
+        routes::checkout::initiate_checkout,
+        routes::checkout::select_shipping,
+        routes::checkout::complete_checkout,
+        routes::auth::login,
+        routes::auth::register,
+        routes::auth::refresh_token,
+        routes::auth::logout,
+        routes::auth::logout_all,
+        routes::auth::list_sessions,
+        routes::auth::revoke_session,
+        routes::auth::create_session,
+        routes::auth::get_session,
+        routes::auth::delete_session,
+        routes::auth::verify_two_factor,
+        routes::two_factor::get_two_factor,
+        routes::two_factor::enroll_two_factor,
+        routes::two_factor::confirm_two_factor,
+        routes::two_factor::disable_two_factor,
+        routes::two_factor::regenerate_backup_codes,
+        routes::oauth::list_providers,
+        routes::oauth::authorize,
+        routes::oauth::oauth_login,
+        routes::oauth::oauth_session,
+        routes::oauth::list_identities,
+        routes::oauth::unlink_identity,
+        routes::auth::request_password_reset,
+        routes::auth::confirm_password_reset,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "products", description = "Product catalog"),
+        (name = "categories", description = "Category tree"),
+        (name = "collections", description = "Published collections"),
+        (name = "cart", description = "Guest and customer carts"),
+        (name = "checkout", description = "Totals, shipping and order placement"),
+        (name = "auth", description = "Customer login, sessions and password resets"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Customer access tokens from `/auth/login`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.components.get_or_insert_with(Default::default).add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// The document, versioned like the API crate
+pub fn document() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    openapi.info.version = env!("CARGO_PKG_VERSION").to_string();
+    openapi
+}
+
+/// The document as pretty-printed JSON
+pub fn document_json() -> String {
+    document().to_pretty_json().expect("OpenAPI document serializes to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` in `value`
+    fn refs<'a>(value: &'a serde_json::Value, found: &mut Vec<&'a str>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", serde_json::Value::String(target)) => found.push(target),
+                        _ => refs(value, found),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_covers_storefront() {
+        let doc: serde_json::Value = serde_json::from_str(&document_json()).unwrap();
+        assert_eq!(doc["openapi"], "3.1.0");
+        assert_eq!(doc["servers"][0]["url"], "/api/v1");
+        for path in ["/products", "/products/{id}", "/categories", "/carts/{cart_id}/items", "/checkout/complete", "/auth/login"] {
+            assert!(doc["paths"].get(path).is_some(), "missing {}", path);
+        }
+        assert!(doc["components"]["securitySchemes"].get("bearer").is_some());
+    }
+
+    #[test]
+    fn test_document_refs_resolve() {
+        let doc: serde_json::Value = serde_json::from_str(&document_json()).unwrap();
+        let mut found = Vec::new();
+        refs(&doc, &mut found);
+        assert!(!found.is_empty());
+        for target in found {
+            let name = target.strip_prefix("#/components/schemas/").expect("schema ref");
+            assert!(doc["components"]["schemas"].get(name).is_some(), "unresolved {}", target);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_response_matches_core_errors() {
+        let response = axum::response::IntoResponse::into_response(rcommerce_core::Error::not_found("Product not found"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let documented = serde_json::to_value(ErrorResponse {
+            error: ErrorDetail {
+                message: body["error"]["message"].as_str().unwrap().to_string(),
+                code: 404,
+                category: body["error"]["category"].as_str().unwrap().to_string(),
+            },
+        })
+        .unwrap();
+        assert_eq!(body, documented);
+    }
+}
@@ -0,0 +1,43 @@
+//! Access Denial API Routes
+//!
+//! Admin endpoint for the access control audit log:
+//! - GET /api/v1/admin/access-denials - Requests refused for a missing client
+//!   certificate or an API key IP allowlist (`?reason=ip_not_allowed`, `?limit=`, `?offset=`)
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::models::{AccessDenial, AccessDenialReason};
+use rcommerce_core::repository::AccessDenialRepository;
+use rcommerce_core::Error;
+
+/// Denials listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters for listing denials
+#[derive(Debug, Deserialize)]
+pub struct ListAccessDenialsQuery {
+    pub reason: Option<AccessDenialReason>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/access-denials
+pub async fn list_access_denials(
+    State(state): State<AppState>,
+    Query(query): Query<ListAccessDenialsQuery>,
+) -> Result<Json<Vec<AccessDenial>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    Ok(Json(state.access_denials.list(query.reason, limit, offset).await?))
+}
+
+/// Router for access denial routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/admin/access-denials", get(list_access_denials))
+}
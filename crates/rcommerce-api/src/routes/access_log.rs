@@ -0,0 +1,41 @@
+//! Access Log API Routes
+//!
+//! Admin endpoint for debugging partner integrations:
+//! - GET /api/v1/admin/access-log - Logged API requests, newest first
+//!   (`?api_key_prefix=`, `?path=` prefix, `?method=`, `?status=`, `?min_status=`,
+//!   `?min_latency_ms=`, `?since=`, `?until=`, `?before_id=`, `?limit=`)
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::models::{AccessLogEntry, AccessLogFilter};
+use rcommerce_core::Error;
+
+/// Page size; the filter is read from the same query string
+#[derive(Debug, Deserialize)]
+pub struct AccessLogLimit {
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/access-log
+pub async fn search_access_log(
+    State(state): State<AppState>,
+    Query(filter): Query<AccessLogFilter>,
+    Query(page): Query<AccessLogLimit>,
+) -> Result<Json<Vec<AccessLogEntry>>, Error> {
+    if !state.access_log.config().enabled {
+        return Err(Error::not_found("The access log is disabled"));
+    }
+    let limit = page.limit.unwrap_or(100);
+    Ok(Json(state.access_log.search(&filter, limit).await?))
+}
+
+/// Router for access log routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/admin/access-log", get(search_access_log))
+}
@@ -0,0 +1,284 @@
+//! Customer Account API Routes
+//!
+//! Self-service for the signed-in customer. Each endpoint needs read access
+//! to its resource in the token's scopes (customer tokens carry the global
+//! `read` scope) and only ever acts on the caller's own account:
+//! - GET    /api/v1/customers/me/orders                        - Order history (orders)
+//! - GET    /api/v1/customers/me/orders/:id                    - Order with its lines and shipment tracking (orders)
+//! - POST   /api/v1/customers/me/orders/:id/reorder            - Add an order's lines to the cart again (orders, carts)
+//! - GET    /api/v1/customers/me/payment-methods               - Saved payment methods (payments)
+//! - POST   /api/v1/customers/me/payment-methods               - Tokenize and save a payment method (payments)
+//! - DELETE /api/v1/customers/me/payment-methods/:id           - Remove a saved payment method (payments)
+//! - POST   /api/v1/customers/me/payment-methods/:id/default   - Make it the default (payments)
+//! - GET    /api/v1/customers/me/notification-preferences      - Notification preferences (customers)
+//! - PUT    /api/v1/customers/me/notification-preferences      - Change them (customers)
+//! - POST   /api/v1/customers/me/close                         - Close the account (customers)
+//!
+//! Invoices are downloaded from `/api/v1/customers/me/invoices`.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::routes::auth::end_all_sessions;
+use crate::routes::cart::{add_line, cart_currency, AddItemRequest};
+use crate::routes::invoice::{pdf_url, ListQuery};
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AccountClosure, AccountOrder, AccountOrderDetail, AddPaymentMethodRequest, CartIdentifier, CartItem,
+    CartWithItems, CustomerNotificationPreferences, SavedPaymentMethod, UpdateNotificationPreferences,
+};
+use rcommerce_core::services::{GeoLocation, Resource};
+use rcommerce_core::Error;
+
+/// The signed-in customer, if the token's scopes allow reading `resource`
+pub(crate) fn account_owner(auth: &JwtAuth, resource: Resource) -> Result<Uuid, Error> {
+    if !auth.can_read(resource) {
+        return Err(Error::unauthorized(format!("Token scopes do not include {}:read", resource.as_str())));
+    }
+    Ok(auth.customer_id)
+}
+
+/// Order history entry
+#[derive(Debug, Serialize)]
+pub struct OrderHistoryItem {
+    #[serde(flatten)]
+    pub order: AccountOrder,
+    pub invoice_pdf_url: String,
+}
+
+/// Order detail response
+#[derive(Debug, Serialize)]
+pub struct AccountOrderResponse {
+    #[serde(flatten)]
+    pub order: AccountOrderDetail,
+    pub invoice_pdf_url: String,
+}
+
+/// An order line that could not be added to the cart
+#[derive(Debug, Serialize)]
+pub struct SkippedLine {
+    pub order_item_id: Uuid,
+    pub title: String,
+    pub reason: String,
+}
+
+/// Reorder response
+#[derive(Debug, Serialize)]
+pub struct ReorderResponse {
+    pub cart: CartWithItems,
+    pub added: Vec<CartItem>,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// Close account request; accounts with a password must confirm it
+#[derive(Debug, Deserialize)]
+pub struct CloseAccountRequest {
+    pub password: Option<String>,
+}
+
+/// GET /api/v1/customers/me/orders
+pub async fn list_orders(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<OrderHistoryItem>>, Error> {
+    let customer_id = account_owner(&auth, Resource::Orders)?;
+    let (limit, offset) = query.limit_offset();
+    let orders = state
+        .accounts
+        .orders(customer_id, limit, offset)
+        .await?
+        .into_iter()
+        .map(|order| OrderHistoryItem { invoice_pdf_url: pdf_url(order.id), order })
+        .collect();
+    Ok(Json(orders))
+}
+
+/// GET /api/v1/customers/me/orders/:id
+pub async fn get_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<AccountOrderResponse>, Error> {
+    let customer_id = account_owner(&auth, Resource::Orders)?;
+    let order = state.accounts.order(customer_id, order_id).await?;
+    Ok(Json(AccountOrderResponse { order, invoice_pdf_url: pdf_url(order_id) }))
+}
+
+/// POST /api/v1/customers/me/orders/:id/reorder
+pub async fn reorder(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    location: Option<Extension<GeoLocation>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<ReorderResponse>, Error> {
+    let customer_id = account_owner(&auth, Resource::Orders)?;
+    account_owner(&auth, Resource::Carts)?;
+    let lines = state.accounts.reorder_lines(customer_id, order_id).await?;
+    let cart = state
+        .cart_service
+        .get_or_create_cart(CartIdentifier::Customer(customer_id), &cart_currency(location))
+        .await?;
+
+    // Lines that can't be added (no longer sold, over a purchase limit...) are reported
+    let mut added = Vec::new();
+    let mut skipped = Vec::new();
+    for line in lines {
+        let product_id = match line.product_id {
+            Some(product_id) if line.available => product_id,
+            _ => {
+                skipped.push(SkippedLine {
+                    order_item_id: line.id,
+                    title: line.title,
+                    reason: "No longer available".to_string(),
+                });
+                continue;
+            }
+        };
+        let request = AddItemRequest { product_id, variant_id: line.variant_id, quantity: line.quantity };
+        match add_line(&state, cart.id, &request).await {
+            Ok(item) => added.push(item),
+            Err(e) => skipped.push(SkippedLine { order_item_id: line.id, title: line.title, reason: e.to_string() }),
+        }
+    }
+
+    let cart = state.cart_service.get_cart_with_items(cart.id).await?;
+    Ok(Json(ReorderResponse { cart, added, skipped }))
+}
+
+/// GET /api/v1/customers/me/payment-methods
+pub async fn list_payment_methods(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<Vec<SavedPaymentMethod>>, Error> {
+    let customer_id = account_owner(&auth, Resource::Payments)?;
+    Ok(Json(state.accounts.payment_methods(customer_id).await?))
+}
+
+/// POST /api/v1/customers/me/payment-methods
+pub async fn add_payment_method(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<AddPaymentMethodRequest>,
+) -> Result<(StatusCode, Json<SavedPaymentMethod>), Error> {
+    let customer_id = account_owner(&auth, Resource::Payments)?;
+    let method = state.accounts.add_payment_method(customer_id, request).await?;
+    Ok((StatusCode::CREATED, Json(method)))
+}
+
+/// DELETE /api/v1/customers/me/payment-methods/:id
+pub async fn remove_payment_method(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let customer_id = account_owner(&auth, Resource::Payments)?;
+    state.accounts.remove_payment_method(customer_id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/customers/me/payment-methods/:id/default
+pub async fn set_default_payment_method(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SavedPaymentMethod>, Error> {
+    let customer_id = account_owner(&auth, Resource::Payments)?;
+    Ok(Json(state.accounts.set_default_payment_method(customer_id, id).await?))
+}
+
+/// GET /api/v1/customers/me/notification-preferences
+pub async fn get_notification_preferences(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<CustomerNotificationPreferences>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    Ok(Json(state.accounts.notification_preferences(customer_id).await?))
+}
+
+/// PUT /api/v1/customers/me/notification-preferences
+pub async fn update_notification_preferences(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(update): Json<UpdateNotificationPreferences>,
+) -> Result<Json<CustomerNotificationPreferences>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    Ok(Json(state.accounts.update_notification_preferences(customer_id, &update).await?))
+}
+
+/// POST /api/v1/customers/me/close
+pub async fn close_account(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CloseAccountRequest>,
+) -> Result<Json<AccountClosure>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    let customer = state
+        .customer_service
+        .find_by_id(customer_id)
+        .await?
+        .ok_or_else(|| Error::not_found("Customer not found"))?;
+
+    // Accounts created through social login have no password to confirm
+    if let Some(password_hash) = customer.password_hash.as_deref() {
+        let password = request
+            .password
+            .as_deref()
+            .ok_or_else(|| Error::validation("Confirm closing the account with its password"))?;
+        let (valid, _) = state.auth_service.verify_password(password, password_hash)?;
+        if !valid {
+            return Err(Error::unauthorized("Invalid password"));
+        }
+    }
+
+    let mut closure = state.accounts.close(customer_id).await?;
+    let (revoked, _) = end_all_sessions(&state, customer_id, "account closed").await?;
+    closure.sessions_revoked = revoked;
+    tracing::info!("Customer {} closed their account", customer_id);
+    Ok(Json(closure))
+}
+
+/// Router for the customer's own account
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/customers/me/orders", get(list_orders))
+        .route("/customers/me/orders/:id", get(get_order))
+        .route("/customers/me/orders/:id/reorder", post(reorder))
+        .route("/customers/me/payment-methods", get(list_payment_methods).post(add_payment_method))
+        .route("/customers/me/payment-methods/:id", delete(remove_payment_method))
+        .route("/customers/me/payment-methods/:id/default", post(set_default_payment_method))
+        .route(
+            "/customers/me/notification-preferences",
+            get(get_notification_preferences).put(update_notification_preferences),
+        )
+        .route("/customers/me/close", post(close_account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(permissions: &[&str]) -> JwtAuth {
+        JwtAuth {
+            customer_id: Uuid::new_v4(),
+            email: "customer@example.com".to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_account_owner() {
+        let customer = auth(&["read"]);
+        assert_eq!(account_owner(&customer, Resource::Payments).unwrap(), customer.customer_id);
+        assert!(account_owner(&auth(&["orders:read"]), Resource::Orders).is_ok());
+        assert!(account_owner(&auth(&["orders:read"]), Resource::Payments).is_err());
+        assert!(account_owner(&auth(&[]), Resource::Customers).is_err());
+    }
+}
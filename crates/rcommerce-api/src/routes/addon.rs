@@ -0,0 +1,123 @@
+//! Order Add-on API Routes
+//!
+//! Gift wrap, gift messages, assembly and other extras chosen at checkout:
+//! - GET    /api/v1/addons                              - Available add-ons
+//! - GET    /api/v1/carts/:cart_id/addons               - Add-ons chosen on a cart
+//! - POST   /api/v1/carts/:cart_id/addons               - Choose an add-on (order-wide or for a cart item)
+//! - DELETE /api/v1/carts/:cart_id/addons/:cart_addon_id - Remove a chosen add-on
+//! - GET    /api/v1/admin/addons                        - All add-ons
+//! - POST   /api/v1/admin/addons                        - Create an add-on
+//! - GET    /api/v1/admin/addons/:id                    - Get an add-on
+//! - PUT    /api/v1/admin/addons/:id                    - Update an add-on
+//! - GET    /api/v1/admin/orders/:id/addons             - Add-ons sold on an order
+//! - GET    /api/v1/admin/orders/:id/pick-list          - Pick list with add-on and delivery instructions
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AddCartAddonRequest, Addon, CartAddon, CreateAddonRequest, OrderAddon, PickList, UpdateAddonRequest,
+};
+use rcommerce_core::Error;
+
+/// GET /api/v1/addons
+pub async fn list_available_addons(State(state): State<AppState>) -> Result<Json<Vec<Addon>>, Error> {
+    Ok(Json(state.addons.list_addons(true).await?))
+}
+
+/// GET /api/v1/carts/:cart_id/addons
+pub async fn list_cart_addons(
+    State(state): State<AppState>,
+    Path(cart_id): Path<Uuid>,
+) -> Result<Json<Vec<CartAddon>>, Error> {
+    Ok(Json(state.addons.cart_addons(cart_id).await?))
+}
+
+/// POST /api/v1/carts/:cart_id/addons
+pub async fn add_cart_addon(
+    State(state): State<AppState>,
+    Path(cart_id): Path<Uuid>,
+    Json(request): Json<AddCartAddonRequest>,
+) -> Result<Json<Vec<CartAddon>>, Error> {
+    Ok(Json(state.addons.add_to_cart(cart_id, request).await?))
+}
+
+/// DELETE /api/v1/carts/:cart_id/addons/:cart_addon_id
+pub async fn remove_cart_addon(
+    State(state): State<AppState>,
+    Path((cart_id, cart_addon_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.addons.remove_from_cart(cart_id, cart_addon_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/addons
+pub async fn list_addons(State(state): State<AppState>) -> Result<Json<Vec<Addon>>, Error> {
+    Ok(Json(state.addons.list_addons(false).await?))
+}
+
+/// POST /api/v1/admin/addons
+pub async fn create_addon(
+    State(state): State<AppState>,
+    Json(request): Json<CreateAddonRequest>,
+) -> Result<(StatusCode, Json<Addon>), Error> {
+    let addon = state.addons.create_addon(request).await?;
+    Ok((StatusCode::CREATED, Json(addon)))
+}
+
+/// GET /api/v1/admin/addons/:id
+pub async fn get_addon(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Addon>, Error> {
+    Ok(Json(state.addons.get_addon(id).await?))
+}
+
+/// PUT /api/v1/admin/addons/:id
+pub async fn update_addon(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateAddonRequest>,
+) -> Result<Json<Addon>, Error> {
+    Ok(Json(state.addons.update_addon(id, request).await?))
+}
+
+/// GET /api/v1/admin/orders/:id/addons
+pub async fn list_order_addons(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<Vec<OrderAddon>>, Error> {
+    Ok(Json(state.addons.order_addons(order_id).await?))
+}
+
+/// GET /api/v1/admin/orders/:id/pick-list
+pub async fn get_pick_list(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<PickList>, Error> {
+    let pick_list = state.addons.pick_list(order_id).await?;
+    Ok(Json(pick_list.with_delivery(state.delivery.for_order(order_id).await?)))
+}
+
+/// Router for storefront add-on routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/addons", get(list_available_addons))
+        .route("/carts/:cart_id/addons", get(list_cart_addons).post(add_cart_addon))
+        .route("/carts/:cart_id/addons/:cart_addon_id", delete(remove_cart_addon))
+}
+
+/// Router for add-on admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/addons", get(list_addons).post(create_addon))
+        .route("/admin/addons/:id", get(get_addon).put(update_addon))
+        .route("/admin/orders/:id/addons", get(list_order_addons))
+        .route("/admin/orders/:id/pick-list", get(get_pick_list))
+}
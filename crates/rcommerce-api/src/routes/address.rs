@@ -0,0 +1,94 @@
+//! Customer Address Book API Routes
+//!
+//! Customers manage their own addresses; admins can manage anyone's. New and
+//! moved addresses are validated with the shipping carrier:
+//! - GET    /api/v1/customers/:id/addresses               - List addresses (defaults first)
+//! - POST   /api/v1/customers/:id/addresses               - Add an address
+//! - GET    /api/v1/customers/:id/addresses/:address_id   - Get an address
+//! - PUT    /api/v1/customers/:id/addresses/:address_id   - Update an address
+//! - DELETE /api/v1/customers/:id/addresses/:address_id   - Delete an address
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{CreateAddressRequest, CustomerAddress, UpdateAddressRequest};
+use rcommerce_core::Error;
+
+/// Customers can only manage their own address book unless admin
+fn authorize(auth: &JwtAuth, customer_id: Uuid) -> Result<(), Error> {
+    if auth.customer_id != customer_id && !auth.is_admin() {
+        return Err(Error::unauthorized("Access denied"));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/customers/:id/addresses
+pub async fn list_addresses(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<Vec<CustomerAddress>>, Error> {
+    authorize(&auth, customer_id)?;
+    Ok(Json(state.addresses.list(customer_id).await?))
+}
+
+/// POST /api/v1/customers/:id/addresses
+pub async fn create_address(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateAddressRequest>,
+) -> Result<(StatusCode, Json<CustomerAddress>), Error> {
+    authorize(&auth, customer_id)?;
+    let address = state.addresses.create(customer_id, request).await?;
+    Ok((StatusCode::CREATED, Json(address)))
+}
+
+/// GET /api/v1/customers/:id/addresses/:address_id
+pub async fn get_address(
+    State(state): State<AppState>,
+    Path((customer_id, address_id)): Path<(Uuid, Uuid)>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<CustomerAddress>, Error> {
+    authorize(&auth, customer_id)?;
+    Ok(Json(state.addresses.get(customer_id, address_id).await?))
+}
+
+/// PUT /api/v1/customers/:id/addresses/:address_id
+pub async fn update_address(
+    State(state): State<AppState>,
+    Path((customer_id, address_id)): Path<(Uuid, Uuid)>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<UpdateAddressRequest>,
+) -> Result<Json<CustomerAddress>, Error> {
+    authorize(&auth, customer_id)?;
+    Ok(Json(state.addresses.update(customer_id, address_id, request).await?))
+}
+
+/// DELETE /api/v1/customers/:id/addresses/:address_id
+pub async fn delete_address(
+    State(state): State<AppState>,
+    Path((customer_id, address_id)): Path<(Uuid, Uuid)>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<StatusCode, Error> {
+    authorize(&auth, customer_id)?;
+    state.addresses.delete(customer_id, address_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for customer address book routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/customers/:id/addresses", get(list_addresses).post(create_address))
+        .route(
+            "/customers/:id/addresses/:address_id",
+            get(get_address).put(update_address).delete(delete_address),
+        )
+}
@@ -1,7 +1,21 @@
 pub mod products;
 
 use crate::state::AppState;
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rcommerce_core::repository::{
+    is_valid_ip_network, ApiKeyRecord, ApiKeyRepository, ApiKeyType, CreateApiKeyRequest,
+};
+use rcommerce_core::services::Scope;
+use rcommerce_core::Error;
 
 /// Get admin dashboard stats
 pub async fn get_stats(State(_state): State<AppState>) -> Json<serde_json::Value> {
@@ -16,17 +30,243 @@ pub async fn get_stats(State(_state): State<AppState>) -> Json<serde_json::Value
     }))
 }
 
+/// An API key as shown to admins; the hash never leaves the server
+#[derive(Debug, Serialize)]
+pub struct ApiKeyView {
+    pub id: Uuid,
+    pub key_prefix: String,
+    pub name: String,
+    pub key_type: &'static str,
+    pub customer_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    pub allowed_origins: Vec<String>,
+    pub allowed_ips: Vec<String>,
+    pub rate_limit_per_minute: Option<i32>,
+    pub is_active: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>,
+}
+
+impl From<ApiKeyRecord> for ApiKeyView {
+    fn from(record: ApiKeyRecord) -> Self {
+        Self {
+            id: record.id,
+            key_prefix: record.key_prefix,
+            name: record.name,
+            key_type: record.key_type.as_str(),
+            customer_id: record.customer_id,
+            scopes: record.scopes,
+            allowed_origins: record.allowed_origins,
+            allowed_ips: record.allowed_ips,
+            rate_limit_per_minute: record.rate_limit_per_minute,
+            is_active: record.is_active,
+            expires_at: record.expires_at,
+            last_used_at: record.last_used_at,
+            created_at: record.created_at,
+            revoked_at: record.revoked_at,
+            revoked_reason: record.revoked_reason,
+        }
+    }
+}
+
+/// Create API key request; mirrors `rcommerce api-key create`
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyBody {
+    pub name: String,
+    #[serde(default)]
+    pub publishable: bool,
+    /// Scopes of a secret key, e.g. `["products:read", "orders:write"]`
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Origins allowed to use a publishable key
+    #[serde(default)]
+    pub origins: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    pub customer_id: Option<Uuid>,
+    pub expires_days: Option<i64>,
+    pub rate_limit_per_minute: Option<i32>,
+}
+
+/// Revoke API key request
+#[derive(Debug, Default, Deserialize)]
+pub struct RevokeApiKeyBody {
+    pub reason: Option<String>,
+}
+
+/// Trim entries and drop empty ones
+fn entries(values: Vec<String>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Check a create request, returning its key type, scopes, origins and IPs
+fn validate_create(
+    body: &CreateApiKeyBody,
+) -> Result<(ApiKeyType, Vec<String>, Vec<String>, Vec<String>), Error> {
+    if body.name.trim().is_empty() {
+        return Err(Error::validation("name is required"));
+    }
+    let origins = entries(body.origins.clone());
+    let allowed_ips = entries(body.allowed_ips.clone());
+    if body.publishable && origins.is_empty() {
+        return Err(Error::validation("Publishable keys need at least one origin"));
+    }
+    if !body.publishable && !origins.is_empty() {
+        return Err(Error::validation("origins only apply to publishable keys"));
+    }
+    if body.rate_limit_per_minute.is_some_and(|limit| limit <= 0) {
+        return Err(Error::validation("rate_limit_per_minute must be positive"));
+    }
+    if body.expires_days.is_some_and(|days| days <= 0) {
+        return Err(Error::validation("expires_days must be positive"));
+    }
+    if let Some(invalid) = allowed_ips.iter().find(|ip| !is_valid_ip_network(ip)) {
+        return Err(Error::validation(format!("Invalid allowed_ips entry '{}'", invalid)));
+    }
+
+    if body.publishable {
+        // Publishable keys can only read the catalog
+        return Ok((ApiKeyType::Publishable, vec!["products:read".to_string()], origins, allowed_ips));
+    }
+    let scopes = entries(body.scopes.clone());
+    if scopes.is_empty() {
+        return Err(Error::validation("A secret key needs at least one scope"));
+    }
+    if let Some((scope, e)) = scopes
+        .iter()
+        .find_map(|scope| Scope::parse(scope).err().map(|e| (scope, e)))
+    {
+        return Err(Error::validation(format!("Invalid scope '{}': {}", scope, e)));
+    }
+    Ok((ApiKeyType::Secret, scopes, origins, allowed_ips))
+}
+
 /// List all API keys (admin only)
-pub async fn list_api_keys(State(_state): State<AppState>) -> Json<serde_json::Value> {
-    Json(serde_json::json!({
-        "api_keys": []
-    }))
+pub async fn list_api_keys(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let keys: Vec<ApiKeyView> = state
+        .api_key_repository
+        .list_all()
+        .await?
+        .into_iter()
+        .map(ApiKeyView::from)
+        .collect();
+    Ok(Json(serde_json::json!({ "api_keys": keys })))
+}
+
+/// Create an API key (admin only); the full key is returned only here
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Json(body): Json<CreateApiKeyBody>,
+) -> Result<(StatusCode, Json<serde_json::Value>), Error> {
+    let (key_type, scopes, allowed_origins, allowed_ips) = validate_create(&body)?;
+    let api_key = match key_type {
+        ApiKeyType::Publishable => state.auth_service.generate_publishable_key(),
+        ApiKeyType::Secret => state.auth_service.generate_api_key(),
+    };
+    let full_key = api_key
+        .full_key
+        .clone()
+        .ok_or_else(|| Error::Other("Generated API key has no secret".to_string()))?;
+
+    let record = state
+        .api_key_repository
+        .create(CreateApiKeyRequest {
+            customer_id: body.customer_id,
+            key_prefix: api_key.prefix,
+            key_hash: api_key.hash,
+            name: body.name.trim().to_string(),
+            scopes,
+            expires_at: body.expires_days.map(|days| Utc::now() + chrono::Duration::days(days)),
+            rate_limit_per_minute: body.rate_limit_per_minute,
+            key_type,
+            allowed_origins,
+            allowed_ips,
+        })
+        .await?;
+
+    tracing::info!("API key {} created from the admin API", record.key_prefix);
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "api_key": ApiKeyView::from(record),
+            "key": full_key,
+        })),
+    ))
+}
+
+/// Revoke an API key by prefix (admin only)
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(prefix): Path<String>,
+    body: Option<Json<RevokeApiKeyBody>>,
+) -> Result<StatusCode, Error> {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let reason = body.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    if !state.api_key_repository.revoke(&prefix, reason).await? {
+        return Err(Error::not_found(format!("API key {} not found", prefix)));
+    }
+    tracing::info!("API key {} revoked from the admin API", prefix);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// Router for admin routes
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/admin/stats", get(get_stats))
-        .route("/admin/api-keys", get(list_api_keys))
+        .route("/admin/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/admin/api-keys/:prefix/revoke", post(revoke_api_key))
         .merge(products::router())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(publishable: bool) -> CreateApiKeyBody {
+        CreateApiKeyBody {
+            name: "Warehouse sync".to_string(),
+            publishable,
+            scopes: vec!["inventory:write".to_string(), " ".to_string()],
+            origins: Vec::new(),
+            allowed_ips: Vec::new(),
+            customer_id: None,
+            expires_days: None,
+            rate_limit_per_minute: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_create_secret_key() {
+        let (key_type, scopes, _, _) = validate_create(&body(false)).unwrap();
+        assert_eq!(key_type, ApiKeyType::Secret);
+        assert_eq!(scopes, vec!["inventory:write".to_string()]);
+
+        let mut invalid = body(false);
+        invalid.scopes = vec!["inventory:destroy".to_string()];
+        assert!(validate_create(&invalid).is_err());
+
+        let mut invalid = body(false);
+        invalid.origins = vec!["https://shop.example.com".to_string()];
+        assert!(validate_create(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_validate_create_publishable_key() {
+        // Publishable keys need an origin and only read the catalog
+        assert!(validate_create(&body(true)).is_err());
+
+        let mut publishable = body(true);
+        publishable.origins = vec!["https://shop.example.com".to_string()];
+        let (key_type, scopes, origins, _) = validate_create(&publishable).unwrap();
+        assert_eq!(key_type, ApiKeyType::Publishable);
+        assert_eq!(scopes, vec!["products:read".to_string()]);
+        assert_eq!(origins.len(), 1);
+    }
+}
@@ -98,9 +98,18 @@ pub async fn list_bundle_components(
                 .into_iter()
                 .map(BundleComponentResponse::from)
                 .collect();
+            // Bundles the components' free stock makes up; null if unlimited
+            let available_quantity = bundle_service
+                .available_quantity(product_id)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to get bundle availability: {}", e);
+                    None
+                });
 
             Json(serde_json::json!({
-                "components": components_json
+                "components": components_json,
+                "available_quantity": available_quantity
             }))
         }
         Err(e) => {
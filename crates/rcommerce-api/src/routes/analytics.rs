@@ -0,0 +1,81 @@
+//! Storefront Analytics API Routes
+//!
+//! First-party funnel events (`[analytics]`), posted in batches by storefront
+//! JavaScript with a publishable key, or by a server-side storefront with an
+//! API key. Requests with `DNT: 1` or `Sec-GPC: 1` are dropped:
+//! - POST /api/v1/storefront/events                         - Track events (publishable key)
+//! - POST /api/v1/analytics/events                          - Track events (API key)
+//! - GET  /api/v1/admin/analytics/funnel                    - Sessions per checkout step (`?from=&to=`, default last 7 days)
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::analytics::{FunnelReport, TrackEventsRequest, TrackEventsResult};
+use rcommerce_core::Error;
+
+/// Query parameters for the funnel
+#[derive(Debug, Deserialize)]
+pub struct FunnelQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Whether the browser asked not to be tracked
+fn do_not_track(headers: &HeaderMap) -> bool {
+    ["dnt", "sec-gpc"]
+        .iter()
+        .any(|name| headers.get(*name).is_some_and(|value| value.as_bytes() == b"1"))
+}
+
+/// POST /api/v1/storefront/events and /api/v1/analytics/events
+pub async fn track_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<TrackEventsRequest>,
+) -> Result<(StatusCode, Json<TrackEventsResult>), Error> {
+    let result = state.analytics.track(request, do_not_track(&headers)).await?;
+    Ok((StatusCode::ACCEPTED, Json(result)))
+}
+
+/// GET /api/v1/admin/analytics/funnel
+pub async fn funnel(
+    State(state): State<AppState>,
+    Query(query): Query<FunnelQuery>,
+) -> Result<Json<FunnelReport>, Error> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::days(7));
+    Ok(Json(state.analytics.funnel(from, to).await?))
+}
+
+/// Router for tracking events with an API key
+pub fn router() -> Router<AppState> {
+    Router::new().route("/analytics/events", post(track_events))
+}
+
+/// Admin router for the checkout funnel
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route("/admin/analytics/funnel", get(funnel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_do_not_track() {
+        let mut headers = HeaderMap::new();
+        assert!(!do_not_track(&headers));
+        headers.insert("dnt", HeaderValue::from_static("0"));
+        assert!(!do_not_track(&headers));
+        headers.insert("sec-gpc", HeaderValue::from_static("1"));
+        assert!(do_not_track(&headers));
+    }
+}
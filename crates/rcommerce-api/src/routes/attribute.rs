@@ -0,0 +1,111 @@
+//! Product Attribute API Routes
+//!
+//! Typed attributes (size, color, material, ...) with allowed values,
+//! assigned to products and variants by code. Filterable ones narrow
+//! `GET /api/v1/products?attr=color:red,blue;width:10..20` and are counted
+//! in its `facets`:
+//! - GET    /api/v1/admin/attributes                                    - List attributes with their options
+//! - POST   /api/v1/admin/attributes                                    - Create an attribute
+//! - GET    /api/v1/admin/attributes/:id                                - Get an attribute
+//! - PUT    /api/v1/admin/attributes/:id                                - Update an attribute (options replace its options)
+//! - DELETE /api/v1/admin/attributes/:id                                - Delete an attribute and its values
+//! - GET    /api/v1/admin/products/:id/attributes                       - Values of a product and its variants
+//! - PUT    /api/v1/admin/products/:id/attributes                       - Replace a product's values
+//! - PUT    /api/v1/admin/products/:id/variants/:variant_id/attributes  - Replace a variant's values
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AssignedAttribute, AttributeWithOptions, CreateAttributeRequest, ProductAttributes, SetAttributeValuesRequest,
+    UpdateAttributeRequest,
+};
+use rcommerce_core::repository::AttributeRepository;
+use rcommerce_core::Error;
+
+/// GET /api/v1/admin/attributes
+pub async fn list_attributes(State(state): State<AppState>) -> Result<Json<Vec<AttributeWithOptions>>, Error> {
+    Ok(Json(state.attributes.repository().list().await?))
+}
+
+/// POST /api/v1/admin/attributes
+pub async fn create_attribute(
+    State(state): State<AppState>,
+    Json(request): Json<CreateAttributeRequest>,
+) -> Result<(StatusCode, Json<AttributeWithOptions>), Error> {
+    let attribute = state.attributes.create_attribute(request).await?;
+    Ok((StatusCode::CREATED, Json(attribute)))
+}
+
+/// GET /api/v1/admin/attributes/:id
+pub async fn get_attribute(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AttributeWithOptions>, Error> {
+    Ok(Json(state.attributes.get_attribute(id).await?))
+}
+
+/// PUT /api/v1/admin/attributes/:id
+pub async fn update_attribute(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateAttributeRequest>,
+) -> Result<Json<AttributeWithOptions>, Error> {
+    Ok(Json(state.attributes.update_attribute(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/attributes/:id
+pub async fn delete_attribute(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.attributes.delete_attribute(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/products/:id/attributes
+pub async fn product_attributes(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<ProductAttributes>, Error> {
+    Ok(Json(state.attributes.product_attributes(product_id).await?))
+}
+
+/// PUT /api/v1/admin/products/:id/attributes
+pub async fn set_product_attributes(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<SetAttributeValuesRequest>,
+) -> Result<Json<Vec<AssignedAttribute>>, Error> {
+    Ok(Json(state.attributes.set_values(product_id, None, request).await?))
+}
+
+/// PUT /api/v1/admin/products/:id/variants/:variant_id/attributes
+pub async fn set_variant_attributes(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<SetAttributeValuesRequest>,
+) -> Result<Json<Vec<AssignedAttribute>>, Error> {
+    Ok(Json(state.attributes.set_values(product_id, Some(variant_id), request).await?))
+}
+
+/// Router for attribute admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/attributes", get(list_attributes).post(create_attribute))
+        .route(
+            "/admin/attributes/:id",
+            get(get_attribute).put(update_attribute).delete(delete_attribute),
+        )
+        .route(
+            "/admin/products/:id/attributes",
+            get(product_attributes).put(set_product_attributes),
+        )
+        .route("/admin/products/:id/variants/:variant_id/attributes", put(set_variant_attributes))
+}
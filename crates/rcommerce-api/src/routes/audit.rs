@@ -0,0 +1,68 @@
+//! Audit Log API Routes
+//!
+//! Admin endpoints for compliance reviews:
+//! - GET /api/v1/admin/audit-log     - Audited mutations, newest first
+//!   (`?actor_id=`, `?actor_type=`, `?resource_type=`, `?resource_id=`, `?method=`,
+//!   `?path=` prefix, `?field=` changed field, `?since=`, `?until=`, `?before_id=`,
+//!   `?limit=`, `?cursor=` from the previous page's `next_cursor`)
+//! - GET /api/v1/admin/audit-log/:id - One entry with its changes
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::models::{AuditEntry, AuditFilter, Cursor};
+use rcommerce_core::Error;
+
+/// Page size and position; the filter is read from the same query string
+#[derive(Debug, Deserialize)]
+pub struct AuditLimit {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+fn ensure_enabled(state: &AppState) -> Result<(), Error> {
+    if !state.audit.config().enabled {
+        return Err(Error::not_found("The audit log is disabled"));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/admin/audit-log
+pub async fn search_audit_log(
+    State(state): State<AppState>,
+    Query(mut filter): Query<AuditFilter>,
+    Query(page): Query<AuditLimit>,
+) -> Result<Json<serde_json::Value>, Error> {
+    ensure_enabled(&state)?;
+    // Entry IDs only grow, so the cursor's ID alone marks the position
+    if let Some(cursor) = Cursor::<i64>::parse(page.cursor.as_deref())? {
+        filter.before_id = Some(filter.before_id.map_or(cursor.id, |id| id.min(cursor.id)));
+    }
+    let limit = page.limit.unwrap_or(100);
+    let page = state.audit.search(&filter, limit).await?;
+    Ok(Json(serde_json::json!({
+        "entries": page.items,
+        "next_cursor": page.next_cursor,
+    })))
+}
+
+/// GET /api/v1/admin/audit-log/:id
+pub async fn get_audit_entry(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<AuditEntry>, Error> {
+    ensure_enabled(&state)?;
+    Ok(Json(state.audit.get(id).await?))
+}
+
+/// Router for audit log routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/audit-log", get(search_audit_log))
+        .route("/admin/audit-log/:id", get(get_audit_entry))
+}
@@ -1,29 +1,50 @@
-use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::middleware::{session, JwtAuth};
+use crate::middleware::geoip::ClientIp;
+use crate::openapi::ErrorResponse;
 use crate::state::AppState;
-use rcommerce_core::{models::CreateCustomerRequest, Error};
+use rcommerce_core::cache::AuthSession;
+use rcommerce_core::jobs::spawn_singleton;
+use rcommerce_core::services::AuthService;
+use rcommerce_core::{
+    models::{CreateCustomerRequest, Customer, CustomerRole, LoginDevice, LoginSession, TwoFactorLoginKind, TwoFactorProof},
+    Error,
+};
+
+/// Code checks per minute from one IP at `/auth/2fa/verify`
+const TWO_FACTOR_VERIFY_LIMIT: u32 = 10;
 
 /// Login request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
 /// Login response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct LoginResponse {
     pub access_token: String,
+    /// Swap for a new access token at `/auth/refresh`; each use returns a new one
     pub refresh_token: String,
     pub token_type: String,
+    /// Seconds until the access token expires
     pub expires_in: i64,
     pub customer: CustomerInfo,
 }
 
 /// Customer info in auth responses
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct CustomerInfo {
     pub id: Uuid,
     pub email: String,
@@ -31,8 +52,55 @@ pub struct CustomerInfo {
     pub last_name: String,
 }
 
+/// Sent instead of tokens or a session when the account has 2FA enabled
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    /// Send to `/auth/2fa/verify` with the code
+    pub challenge_token: String,
+    /// Seconds until the challenge expires
+    pub expires_in: i64,
+    /// Accepted proofs: `totp` and `backup_code`
+    pub methods: Vec<String>,
+}
+
+/// Second step of a login with 2FA; send `code` or `backup_code`
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct TwoFactorVerifyRequest {
+    pub challenge_token: String,
+    /// Six-digit code from the authenticator app
+    pub code: Option<String>,
+    pub backup_code: Option<String>,
+}
+
+/// Session response; the session token itself is only sent as a cookie
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SessionResponse {
+    pub customer_id: Uuid,
+    pub email: String,
+    /// Send back in the CSRF header on unsafe requests
+    pub csrf_token: String,
+    /// Unix timestamp after which the session must be renewed by logging in
+    pub expires_at: i64,
+    /// Only set by login
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer: Option<CustomerInfo>,
+}
+
+impl SessionResponse {
+    fn new(session: AuthSession, customer: Option<CustomerInfo>) -> Self {
+        Self {
+            customer_id: session.customer_id,
+            email: session.email,
+            csrf_token: session.csrf_token,
+            expires_at: session.expires_at,
+            customer,
+        }
+    }
+}
+
 /// Register request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct RegisterRequest {
     pub email: String,
     pub password: String,
@@ -42,51 +110,234 @@ pub struct RegisterRequest {
 }
 
 /// Register response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct RegisterResponse {
     pub customer: CustomerInfo,
     pub message: String,
 }
 
 /// Refresh token request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
 /// Refresh token response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct RefreshTokenResponse {
     pub access_token: String,
+    /// Replaces the refresh token that was sent, which no longer works
+    pub refresh_token: String,
     pub token_type: String,
     pub expires_in: i64,
 }
 
+/// Logout request; without a body the session of the access token ends
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
+}
+
+/// Logout-all response
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LogoutAllResponse {
+    /// Login sessions revoked
+    pub sessions_revoked: u64,
+    /// Cookie sessions ended
+    pub cookie_sessions_ended: u64,
+}
+
+/// A signed-in device
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DeviceSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// The session of the access token making the request
+    pub current: bool,
+}
+
+impl DeviceSession {
+    fn new(session: LoginSession, current: Option<Uuid>) -> Self {
+        Self {
+            current: current == Some(session.id),
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// Signed-in devices response
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct DeviceSessionsResponse {
+    pub sessions: Vec<DeviceSession>,
+}
+
 /// Password reset request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct PasswordResetRequest {
     pub email: String,
 }
 
 /// Password reset confirm request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct PasswordResetConfirmRequest {
     pub token: String,
     pub password: String,
 }
 
 /// Password reset response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct PasswordResetResponse {
     pub message: String,
     pub token: Option<String>, // Only for demo/testing
 }
 
 /// Login endpoint
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = LoginResponse),
+        (status = 202, description = "Two-factor code required; complete at /auth/2fa/verify", body = TwoFactorChallengeResponse),
+        (status = 401, description = "Invalid email or password", body = ErrorResponse),
+    )
+)]
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, Error> {
+) -> Result<Response, Error> {
+    let customer = verify_credentials(&state, &payload).await?;
+    let device = login_device(&headers, client_ip);
+
+    if state.two_factor.is_enabled(customer.id).await? {
+        return two_factor_challenge(&state, customer.id, TwoFactorLoginKind::Token, &device).await;
+    }
+    Ok(Json(token_login(&state, customer, &device).await?).into_response())
+}
+
+/// Start a login session and issue its tokens
+pub(crate) async fn token_login(state: &AppState, customer: Customer, device: &LoginDevice) -> Result<LoginResponse, Error> {
+    // Start a login session; its refresh token renews the access token
+    let issued = state.login_sessions.start(customer.id, device, Utc::now()).await?;
+
+    // Generate tokens with role-based permissions
+    let permissions = token_permissions(state, customer.id, &customer.role).await?;
+    let access_token = state.auth_service.generate_session_access_token(
+        customer.id,
+        &customer.email,
+        permissions,
+        issued.session.id,
+    )?;
+
+    Ok(LoginResponse {
+        access_token,
+        refresh_token: issued.refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: state.auth_service.access_token_ttl_secs(),
+        customer: CustomerInfo {
+            id: customer.id,
+            email: customer.email,
+            first_name: customer.first_name,
+            last_name: customer.last_name,
+        },
+    })
+}
+
+/// 202 with a challenge for the second factor, in place of a login
+pub(crate) async fn two_factor_challenge(
+    state: &AppState,
+    customer_id: Uuid,
+    kind: TwoFactorLoginKind,
+    device: &LoginDevice,
+) -> Result<Response, Error> {
+    let issued = state.two_factor.start_challenge(customer_id, kind, device, Utc::now()).await?;
+    tracing::info!("Password accepted for customer {}; waiting for a two-factor code", customer_id);
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(TwoFactorChallengeResponse {
+            two_factor_required: true,
+            challenge_token: issued.token,
+            expires_in: state.two_factor.config().challenge_ttl_secs as i64,
+            methods: vec!["totp".to_string(), "backup_code".to_string()],
+        }),
+    )
+        .into_response())
+}
+
+/// Complete a login with a two-factor code
+///
+/// Returns what the login would have: tokens for `/auth/login`, or the
+/// session cookies for `/auth/session`. Each challenge accepts a few wrong
+/// codes before the login must start over.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/verify",
+    tag = "auth",
+    request_body = TwoFactorVerifyRequest,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = LoginResponse),
+        (status = 201, description = "Session started; the session and CSRF cookies are set", body = SessionResponse),
+        (status = 401, description = "Invalid code, or the challenge expired", body = ErrorResponse),
+        (status = 429, description = "Too many attempts", body = ErrorResponse),
+    )
+)]
+pub async fn verify_two_factor(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
+    Json(payload): Json<TwoFactorVerifyRequest>,
+) -> Result<Response, Error> {
+    let device = login_device(&headers, client_ip);
+    let limit_key = format!("2fa:{}", device.ip_address.as_deref().unwrap_or("unknown"));
+    if !state.auth_rate_limiter.check_and_increment_with_limit(&limit_key, TWO_FACTOR_VERIFY_LIMIT).await {
+        tracing::warn!("Two-factor rate limit exceeded for {}", limit_key);
+        return Err(Error::HttpError(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many two-factor attempts; try again in a minute".to_string(),
+        ));
+    }
+
+    let proof = TwoFactorProof { code: payload.code, backup_code: payload.backup_code };
+    let challenge = state
+        .two_factor
+        .verify_challenge(&payload.challenge_token, &proof, Utc::now())
+        .await?;
+    let customer = state
+        .customer_service
+        .find_by_id(challenge.customer_id)
+        .await?
+        .ok_or_else(|| Error::unauthorized("Customer not found"))?;
+
+    match challenge.kind() {
+        TwoFactorLoginKind::Token => Ok(Json(token_login(&state, customer, &device).await?).into_response()),
+        TwoFactorLoginKind::Cookie => cookie_login(&state, customer).await,
+    }
+}
+
+/// User agent and client IP of a login
+pub(crate) fn login_device(headers: &HeaderMap, client_ip: Option<std::net::IpAddr>) -> LoginDevice {
+    let ip_address = client_ip.map(|ip| ip.to_string());
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|agent| agent.chars().take(512).collect());
+    LoginDevice { user_agent, ip_address }
+}
+
+/// Check an email and password, upgrading legacy password hashes
+async fn verify_credentials(state: &AppState, payload: &LoginRequest) -> Result<Customer, Error> {
     // Find customer by email
     let customer = state
         .customer_service
@@ -119,27 +370,145 @@ pub async fn login(
         }
     }
 
-    // Generate tokens with role-based permissions
-    let access_token = state
-        .auth_service
-        .generate_access_token(customer.id, &customer.email, &customer.role)?;
-    let refresh_token = state.auth_service.generate_refresh_token(customer.id)?;
+    Ok(customer)
+}
 
-    Ok(Json(LoginResponse {
-        access_token,
-        refresh_token,
-        token_type: "Bearer".to_string(),
-        expires_in: 24 * 3600, // 24 hours
-        customer: CustomerInfo {
-            id: customer.id,
-            email: customer.email,
-            first_name: customer.first_name,
-            last_name: customer.last_name,
-        },
-    }))
+pub(crate) fn session_store(state: &AppState) -> Result<&rcommerce_core::cache::AuthSessionStore, Error> {
+    state
+        .sessions
+        .as_deref()
+        .ok_or_else(|| Error::not_found("Cookie sessions are not enabled"))
+}
+
+/// Session login for browser storefronts
+/// Sets the httpOnly session cookie and the CSRF cookie
+#[utoipa::path(
+    post,
+    path = "/auth/session",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 201, description = "Session started; the session and CSRF cookies are set", body = SessionResponse),
+        (status = 202, description = "Two-factor code required; complete at /auth/2fa/verify", body = TwoFactorChallengeResponse),
+        (status = 401, description = "Invalid email or password", body = ErrorResponse),
+    )
+)]
+pub async fn create_session(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Response, Error> {
+    session_store(&state)?;
+    let customer = verify_credentials(&state, &payload).await?;
+
+    if state.two_factor.is_enabled(customer.id).await? {
+        let device = login_device(&headers, client_ip);
+        return two_factor_challenge(&state, customer.id, TwoFactorLoginKind::Cookie, &device).await;
+    }
+    cookie_login(&state, customer).await
+}
+
+/// Start a cookie session and set its cookies
+pub(crate) async fn cookie_login(state: &AppState, customer: Customer) -> Result<Response, Error> {
+    let store = session_store(state)?;
+    let permissions = token_permissions(state, customer.id, &customer.role).await?;
+
+    let (token, auth_session) = store
+        .create(customer.id, &customer.email, permissions)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create session: {}", e)))?;
+    tracing::info!("Session created for customer {}", customer.id);
+
+    let cookies = session::session_cookies(store.config(), &token, &auth_session);
+    let customer = CustomerInfo {
+        id: customer.id,
+        email: customer.email,
+        first_name: customer.first_name,
+        last_name: customer.last_name,
+    };
+    Ok((
+        StatusCode::CREATED,
+        AppendHeaders(cookies),
+        Json(SessionResponse::new(auth_session, Some(customer))),
+    )
+        .into_response())
+}
+
+/// Current session, e.g. to recover the CSRF token after a page load
+#[utoipa::path(
+    get,
+    path = "/auth/session",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current session", body = SessionResponse),
+        (status = 401, description = "No session", body = ErrorResponse),
+    )
+)]
+pub async fn get_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SessionResponse>, Error> {
+    session_store(&state)?;
+    let (_, auth_session) = session::request_session(&state, &Method::GET, &headers)
+        .await
+        .map_err(|_| Error::unauthorized("Session could not be loaded"))?
+        .ok_or_else(|| Error::unauthorized("No active session"))?;
+    Ok(Json(SessionResponse::new(auth_session, None)))
+}
+
+/// Session logout; requires the CSRF header
+#[utoipa::path(
+    delete,
+    path = "/auth/session",
+    tag = "auth",
+    responses(
+        (status = 204, description = "Session ended; the cookies are cleared"),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+    )
+)]
+pub async fn delete_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let store = session_store(&state)?;
+    let current = session::request_session(&state, &Method::DELETE, &headers).await.map_err(|status| match status {
+        StatusCode::FORBIDDEN => Error::HttpError(StatusCode::FORBIDDEN, "Missing or invalid CSRF token".to_string()),
+        _ => Error::Other("Failed to load session".to_string()),
+    })?;
+
+    if let Some((token, auth_session)) = current {
+        store
+            .destroy(&token, auth_session.customer_id)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to end session: {}", e)))?;
+        tracing::info!("Session ended for customer {}", auth_session.customer_id);
+    }
+
+    Ok((StatusCode::NO_CONTENT, AppendHeaders(session::clear_cookies(store.config()))).into_response())
+}
+
+/// Permissions for an access token: the base role plus assigned staff roles
+async fn token_permissions(state: &AppState, customer_id: Uuid, role: &CustomerRole) -> Result<Vec<String>, Error> {
+    Ok(state
+        .roles
+        .effective_permissions(customer_id)
+        .await?
+        .map(|effective| effective.permissions)
+        .unwrap_or_else(|| role.permissions()))
 }
 
 /// Register endpoint
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Customer registered", body = RegisterResponse),
+        (status = 400, description = "Invalid registration details", body = ErrorResponse),
+    )
+)]
 pub async fn register(
     State(state): State<AppState>,
     Json(payload): Json<RegisterRequest>,
@@ -183,42 +552,224 @@ pub async fn register(
 }
 
 /// Refresh access token
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "A new access token and refresh token", body = RefreshTokenResponse),
+        (status = 401, description = "Invalid, reused, revoked or expired refresh token", body = ErrorResponse),
+    )
+)]
 pub async fn refresh_token(
     State(state): State<AppState>,
     Json(payload): Json<RefreshTokenRequest>,
 ) -> Result<Json<RefreshTokenResponse>, Error> {
-    // Verify refresh token
-    let claims = state.auth_service.verify_token(&payload.refresh_token)?;
-
-    // Ensure it's a refresh token
-    if claims.token_type != rcommerce_core::services::TokenType::Refresh {
-        return Err(Error::unauthorized("Invalid token type"));
-    }
+    // Rotate the refresh token; reusing an old one revokes the session
+    let issued = state.login_sessions.refresh(&payload.refresh_token, Utc::now()).await?;
+    let customer_id = issued.session.customer_id;
 
     // Fetch customer to get their current role
     let customer = state
         .customer_service
-        .find_by_id(claims.sub)
+        .find_by_id(customer_id)
         .await?
         .ok_or_else(|| Error::unauthorized("Customer not found"))?;
 
     // Generate new access token with role-based permissions
-    let access_token = state
-        .auth_service
-        .generate_access_token(claims.sub, &customer.email, &customer.role)?;
+    let permissions = token_permissions(&state, customer_id, &customer.role).await?;
+    let access_token = state.auth_service.generate_session_access_token(
+        customer_id,
+        &customer.email,
+        permissions,
+        issued.session.id,
+    )?;
 
     Ok(Json(RefreshTokenResponse {
         access_token,
+        refresh_token: issued.refresh_token,
         token_type: "Bearer".to_string(),
-        expires_in: 24 * 3600, // 24 hours
+        expires_in: state.auth_service.access_token_ttl_secs(),
     }))
 }
 
+/// Login session of the bearer token, if it was issued for one
+async fn current_session_id(state: &AppState, headers: &HeaderMap) -> Option<Uuid> {
+    let header = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let token = AuthService::extract_bearer_token(header)?;
+    state.verify_access_token(token).await.ok()?.sid
+}
+
+/// Log out one device
+///
+/// Revokes the session of the given refresh token, or else the session the
+/// access token was issued for. Access tokens issued for the session stop
+/// working too.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    request_body(content = LogoutRequest, description = "Optional; defaults to the current session"),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 404, description = "No such active session", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    headers: HeaderMap,
+    body: Option<Json<LogoutRequest>>,
+) -> Result<StatusCode, Error> {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let session_id = match body.refresh_token {
+        Some(refresh_token) => state
+            .login_sessions
+            .refresh_session_id(&refresh_token, Utc::now())
+            .await?
+            .ok_or_else(|| Error::not_found("Session not found"))?,
+        None => current_session_id(&state, &headers)
+            .await
+            .ok_or_else(|| Error::validation("refresh_token is required when the access token has no session"))?,
+    };
+    state.login_sessions.revoke(auth.customer_id, session_id, "logout").await?;
+    tracing::info!("Login session {} ended for customer {}", session_id, auth.customer_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Log out every device: revokes all login sessions and ends cookie sessions
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Sessions ended", body = LogoutAllResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn logout_all(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<LogoutAllResponse>, Error> {
+    let (sessions_revoked, cookie_sessions_ended) = end_all_sessions(&state, auth.customer_id, "logout all").await?;
+    Ok(Json(LogoutAllResponse { sessions_revoked, cookie_sessions_ended }))
+}
+
+/// Revoke every login session and end every cookie session of a customer
+pub(crate) async fn end_all_sessions(state: &AppState, customer_id: Uuid, reason: &str) -> Result<(u64, u64), Error> {
+    let revoked = state.login_sessions.revoke_all(customer_id, reason).await?;
+    let mut ended = 0;
+    if let Some(ref sessions) = state.sessions {
+        ended = sessions
+            .destroy_all(customer_id)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to end sessions: {}", e)))? as u64;
+    }
+    tracing::info!(
+        "Revoked {} login sessions and ended {} cookie sessions for customer {} ({})",
+        revoked,
+        ended,
+        customer_id,
+        reason
+    );
+    Ok((revoked, ended))
+}
+
+/// Signed-in devices of the current customer
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Active login sessions, most recently used first", body = DeviceSessionsResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceSessionsResponse>, Error> {
+    let current = current_session_id(&state, &headers).await;
+    let sessions = state
+        .login_sessions
+        .list(auth.customer_id, Utc::now())
+        .await?
+        .into_iter()
+        .map(|session| DeviceSession::new(session, current))
+        .collect();
+    Ok(Json(DeviceSessionsResponse { sessions }))
+}
+
+/// Sign out one device by session ID
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "Login session ID")),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 404, description = "No such active session", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.login_sessions.revoke(auth.customer_id, id, "revoked by customer").await?;
+    tracing::info!("Login session {} revoked by customer {}", id, auth.customer_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Periodically delete login sessions that ended over a week ago, and
+/// two-factor challenges and social logins that expired over a day ago
+pub fn spawn_session_purge(state: &AppState) {
+    let login_sessions = state.login_sessions.clone();
+    let two_factor = state.two_factor.clone();
+    let oauth = state.oauth.clone();
+    let interval = std::time::Duration::from_secs(3600);
+    spawn_singleton(state.locks.clone(), "login_session_purge", interval, move || {
+        let login_sessions = login_sessions.clone();
+        let two_factor = two_factor.clone();
+        let oauth = oauth.clone();
+        async move {
+            match login_sessions.purge_expired(Utc::now()).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} ended login sessions", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Login session purge failed: {}", e),
+            }
+            match two_factor.purge_expired(Utc::now()).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} expired two-factor challenges", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Two-factor challenge purge failed: {}", e),
+            }
+            match oauth.purge(Utc::now()).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} expired social logins", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Social login purge failed: {}", e),
+            }
+        }
+    });
+}
+
 /// Request password reset
 /// Generates a reset token and sends it via email
 /// 
 /// Security note: In production, the token is NEVER returned in the response.
 /// It is only returned in development builds for testing purposes.
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset",
+    tag = "auth",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 200, description = "Reset email sent if the account exists", body = PasswordResetResponse),
+    )
+)]
 pub async fn request_password_reset(
     State(state): State<AppState>,
     Json(payload): Json<PasswordResetRequest>,
@@ -267,6 +818,16 @@ pub async fn request_password_reset(
 
 /// Confirm password reset
 /// Validates token and updates password
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    tag = "auth",
+    request_body = PasswordResetConfirmRequest,
+    responses(
+        (status = 200, description = "Password changed", body = PasswordResetResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
 pub async fn confirm_password_reset(
     State(state): State<AppState>,
     Json(payload): Json<PasswordResetConfirmRequest>,
@@ -289,6 +850,11 @@ pub async fn confirm_password_reset(
 
     tracing::info!("Password reset successful for customer {}", claims.sub);
 
+    // A reset usually follows a compromise, so log out every device
+    if let Err(e) = end_all_sessions(&state, claims.sub, "password reset").await {
+        tracing::warn!("Failed to end sessions for customer {}: {}", claims.sub, e);
+    }
+
     Ok(Json(PasswordResetResponse {
         message: "Password reset successful. Please log in with your new password.".to_string(),
         token: None,
@@ -303,6 +869,8 @@ pub fn public_router() -> Router<AppState> {
         .route("/auth/login", post(login))
         .route("/auth/register", post(register))
         .route("/auth/refresh", post(refresh_token))
+        .route("/auth/session", post(create_session).get(get_session).delete(delete_session))
+        .route("/auth/2fa/verify", post(verify_two_factor))
 }
 
 /// Protected auth routes (API key required)
@@ -312,4 +880,25 @@ pub fn protected_router() -> Router<AppState> {
         // Password reset routes require API key to prevent abuse
         .route("/auth/password-reset", post(request_password_reset))
         .route("/auth/password-reset/confirm", post(confirm_password_reset))
+        // Login sessions of the signed-in customer
+        .route("/auth/logout", post(logout))
+        .route("/auth/logout-all", post(logout_all))
+        .route("/auth/sessions", get(list_sessions))
+        .route("/auth/sessions/:id", delete(revoke_session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_device() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, "Mozilla/5.0".parse().unwrap());
+
+        let device = login_device(&headers, Some("10.0.0.2".parse().unwrap()));
+        assert_eq!(device.user_agent.as_deref(), Some("Mozilla/5.0"));
+        assert_eq!(device.ip_address.as_deref(), Some("10.0.0.2"));
+        assert!(login_device(&HeaderMap::new(), None).ip_address.is_none());
+    }
 }
@@ -0,0 +1,134 @@
+//! Automation API Routes
+//!
+//! Rules tag or hold orders and notify Slack when order, payment and
+//! customer events match their conditions; the `automation` job runs them
+//! (`[automation]`):
+//! - GET    /api/v1/admin/automation/rules           - Rules (`?trigger=order_paid`)
+//! - POST   /api/v1/admin/automation/rules           - Create a rule
+//! - POST   /api/v1/admin/automation/rules/test      - Dry-run an unsaved rule against an order or customer
+//! - GET    /api/v1/admin/automation/rules/:id       - Get a rule
+//! - PUT    /api/v1/admin/automation/rules/:id       - Update a rule
+//! - DELETE /api/v1/admin/automation/rules/:id       - Delete a rule with its runs
+//! - POST   /api/v1/admin/automation/rules/:id/test  - Dry-run a saved rule
+//! - GET    /api/v1/admin/automation/runs            - Execution log (`?rule_id=...&order_id=...`)
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AutomationDryRun, AutomationRule, AutomationRun, AutomationTrigger, CreateAutomationRuleRequest,
+    DryRunSubject, TestAutomationRuleRequest, UpdateAutomationRuleRequest,
+};
+use rcommerce_core::Error;
+
+/// Runs listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters for listing rules
+#[derive(Debug, Deserialize)]
+pub struct ListRulesQuery {
+    pub trigger: Option<AutomationTrigger>,
+}
+
+/// Query parameters for listing runs
+#[derive(Debug, Deserialize)]
+pub struct ListRunsQuery {
+    pub rule_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/automation/rules
+pub async fn list_rules(
+    State(state): State<AppState>,
+    Query(query): Query<ListRulesQuery>,
+) -> Result<Json<Vec<AutomationRule>>, Error> {
+    let rules = state.automation.list_rules(query.trigger).await?;
+    Ok(Json(rules))
+}
+
+/// POST /api/v1/admin/automation/rules
+pub async fn create_rule(
+    State(state): State<AppState>,
+    Json(request): Json<CreateAutomationRuleRequest>,
+) -> Result<(StatusCode, Json<AutomationRule>), Error> {
+    let rule = state.automation.create_rule(request).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// POST /api/v1/admin/automation/rules/test
+pub async fn test_rule(
+    State(state): State<AppState>,
+    Json(request): Json<TestAutomationRuleRequest>,
+) -> Result<Json<AutomationDryRun>, Error> {
+    let dry_run = state.automation.dry_run(&request.rule, request.subject).await?;
+    Ok(Json(dry_run))
+}
+
+/// GET /api/v1/admin/automation/rules/:id
+pub async fn get_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AutomationRule>, Error> {
+    let rule = state.automation.get_rule(id).await?;
+    Ok(Json(rule))
+}
+
+/// PUT /api/v1/admin/automation/rules/:id
+pub async fn update_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateAutomationRuleRequest>,
+) -> Result<Json<AutomationRule>, Error> {
+    let rule = state.automation.update_rule(id, request).await?;
+    Ok(Json(rule))
+}
+
+/// DELETE /api/v1/admin/automation/rules/:id
+pub async fn delete_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.automation.delete_rule(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/automation/rules/:id/test
+pub async fn test_saved_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(subject): Json<DryRunSubject>,
+) -> Result<Json<AutomationDryRun>, Error> {
+    let dry_run = state.automation.dry_run_rule(id, subject).await?;
+    Ok(Json(dry_run))
+}
+
+/// GET /api/v1/admin/automation/runs
+pub async fn list_runs(
+    State(state): State<AppState>,
+    Query(query): Query<ListRunsQuery>,
+) -> Result<Json<Vec<AutomationRun>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let runs = state.automation.list_runs(query.rule_id, query.order_id, limit).await?;
+    Ok(Json(runs))
+}
+
+/// Admin router for automation rules and their runs
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/automation/rules", get(list_rules).post(create_rule))
+        .route("/admin/automation/rules/test", post(test_rule))
+        .route(
+            "/admin/automation/rules/:id",
+            get(get_rule).put(update_rule).delete(delete_rule),
+        )
+        .route("/admin/automation/rules/:id/test", post(test_saved_rule))
+        .route("/admin/automation/runs", get(list_runs))
+}
@@ -0,0 +1,132 @@
+//! Cache warmup routes
+//!
+//! GET  /api/v1/admin/cache/warmup - Last warmup report
+//! POST /api/v1/admin/cache/warm   - Re-run cache warming now
+//! POST /api/v1/admin/cache/purge  - Purge cached responses by surrogate key or URL pattern
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::Error;
+
+/// Request body for purging cached responses
+#[derive(Debug, Deserialize)]
+pub struct PurgeRequest {
+    /// Purge responses tagged with this surrogate key
+    pub surrogate_key: Option<String>,
+    /// Purge responses whose path matches this pattern (`*` matches anything)
+    pub url_pattern: Option<String>,
+    /// Purge every cached response
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// GET /ready
+/// Readiness probe: 503 until the startup cache warmup has finished.
+/// Read replicas are listed but don't affect readiness; reads fall back
+/// to the primary.
+pub async fn readiness(State(state): State<AppState>) -> impl IntoResponse {
+    if state.warmup_state.is_ready() {
+        (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "status": "ready",
+                "warmup": state.warmup_state.last_report().await,
+                "read_replicas": state.db.replica_status(),
+            })),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "warming" })),
+        )
+    }
+}
+
+/// GET /api/v1/admin/cache/warmup
+pub async fn get_warmup_report(State(state): State<AppState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "ready": state.warmup_state.is_ready(),
+        "report": state.warmup_state.last_report().await,
+    }))
+}
+
+/// POST /api/v1/admin/cache/warm
+pub async fn warm_cache(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let report = state.cache_warmer.warm().await;
+    state.warmup_state.record(report.clone()).await;
+
+    Json(serde_json::json!({
+        "complete": report.is_complete(),
+        "report": report,
+    }))
+}
+
+/// POST /api/v1/admin/cache/purge
+///
+/// CDNs in front of the API purge the same surrogate keys from the
+/// `Surrogate-Key` headers; this only empties this instance's cache.
+pub async fn purge_cache(
+    State(state): State<AppState>,
+    Json(request): Json<PurgeRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let cache = &state.response_cache;
+    let purged = match (request.surrogate_key.as_deref(), request.url_pattern.as_deref(), request.all) {
+        (Some(key), None, false) if !key.trim().is_empty() => cache.purge_surrogate_key(key.trim()),
+        (None, Some(pattern), false) if pattern.starts_with('/') || pattern.starts_with('*') => {
+            cache.purge_url_pattern(pattern)
+        }
+        (None, None, true) => cache.purge_all(),
+        _ => {
+            return Err(Error::validation(
+                "Give one of surrogate_key, url_pattern (a path, * matches anything) or all",
+            ))
+        }
+    };
+    tracing::info!("Purged {} cached responses", purged);
+
+    Ok(Json(serde_json::json!({
+        "purged": purged,
+        "remaining": cache.len(),
+    })))
+}
+
+/// Spawn the startup warmup in the background. The readiness probe flips once
+/// it finishes, or after the configured timeout so a slow database can't keep
+/// the instance out of rotation forever.
+pub fn spawn_startup_warmup(state: &AppState) {
+    let warmer = state.cache_warmer.clone();
+    let warmup_state = state.warmup_state.clone();
+
+    if !warmer.config().enabled {
+        warmup_state.mark_ready();
+        return;
+    }
+
+    let timeout = std::time::Duration::from_secs(warmer.config().timeout_secs);
+    tokio::spawn(async move {
+        match tokio::time::timeout(timeout, warmer.warm()).await {
+            Ok(report) => warmup_state.record(report).await,
+            Err(_) => {
+                tracing::warn!("Cache warmup timed out after {:?}, marking ready anyway", timeout);
+                warmup_state.mark_ready();
+            }
+        }
+    });
+}
+
+/// Router for cache admin routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/cache/warmup", get(get_warmup_report))
+        .route("/admin/cache/warm", post(warm_cache))
+        .route("/admin/cache/purge", post(purge_cache))
+}
@@ -0,0 +1,49 @@
+//! Traffic capture routes
+//!
+//! GET    /api/v1/admin/capture - Download captured request/response pairs
+//! DELETE /api/v1/admin/capture - Clear the capture buffer
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+
+use crate::state::AppState;
+
+/// GET /api/v1/admin/capture
+/// Download the capture buffer as a JSON array, suitable for `rcommerce replay`
+pub async fn download_capture(State(state): State<AppState>) -> impl IntoResponse {
+    if !state.traffic_capture.is_enabled() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Traffic capture is disabled" })),
+        )
+            .into_response();
+    }
+
+    let exchanges = state.traffic_capture.snapshot().await;
+    let filename = format!(
+        "attachment; filename=\"capture-{}.json\"",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+
+    (
+        [(header::CONTENT_DISPOSITION, filename)],
+        Json(exchanges),
+    )
+        .into_response()
+}
+
+/// DELETE /api/v1/admin/capture
+pub async fn clear_capture(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let cleared = state.traffic_capture.clear().await;
+    Json(serde_json::json!({ "cleared": cleared }))
+}
+
+/// Router for capture admin routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/admin/capture", get(download_capture).delete(clear_capture))
+}
@@ -8,6 +8,7 @@
 //! - Coupon application
 
 use crate::middleware::JwtAuth;
+use crate::openapi::ErrorResponse;
 use crate::state::AppState;
 use axum::{
     extract::{Extension, Path, State},
@@ -16,17 +17,35 @@ use axum::{
     Json, Router,
 };
 use rcommerce_core::{
-    models::{AddToCartInput, ApplyCouponInput, CartIdentifier, CartWithItems, UpdateCartItemInput},
-    services::cart_service::ProductDetails,
+    models::{AddToCartInput, ApplyCouponInput, CartIdentifier, CartItem, CartWithItems, UpdateCartItemInput},
+    order::OrderCalculator,
+    services::{cart_service::ProductDetails, GeoLocation},
     Error,
 };
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
 use uuid::Uuid;
 
+/// Currency for new carts: the client's detected (or chosen) currency, else USD
+pub(crate) fn cart_currency(location: Option<Extension<GeoLocation>>) -> String {
+    location
+        .map(|Extension(location)| location.currency.to_string())
+        .unwrap_or_else(|| "USD".to_string())
+}
+
 /// Create a new guest cart
+#[utoipa::path(
+    post,
+    path = "/carts/guest",
+    tag = "cart",
+    responses(
+        (status = 200, description = "New empty guest cart", body = CartWithItems),
+    )
+)]
 pub async fn create_guest_cart(
     State(state): State<AppState>,
+    location: Option<Extension<GeoLocation>>,
 ) -> Result<Json<CartWithItems>, Error> {
     // Generate session token for guest cart
     let session_token = format!("sess_{}", Uuid::new_v4().to_string().replace("-", ""));
@@ -34,7 +53,7 @@ pub async fn create_guest_cart(
     // Get or create cart via service
     let cart = state
         .cart_service
-        .get_or_create_cart(CartIdentifier::Session(session_token.clone()), "USD")
+        .get_or_create_cart(CartIdentifier::Session(session_token.clone()), &cart_currency(location))
         .await?;
 
     // Return cart with items (empty for new cart)
@@ -44,14 +63,24 @@ pub async fn create_guest_cart(
 }
 
 /// Get or create customer cart
+#[utoipa::path(
+    get,
+    path = "/carts/me",
+    tag = "cart",
+    responses(
+        (status = 200, description = "The customer's cart, created if needed", body = CartWithItems),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn get_customer_cart(
     State(state): State<AppState>,
     Extension(jwt_auth): Extension<JwtAuth>,
+    location: Option<Extension<GeoLocation>>,
 ) -> Result<Json<CartWithItems>, Error> {
     // Get or create cart for authenticated customer
     let cart = state
         .cart_service
-        .get_or_create_cart(CartIdentifier::Customer(jwt_auth.customer_id), "USD")
+        .get_or_create_cart(CartIdentifier::Customer(jwt_auth.customer_id), &cart_currency(location))
         .await?;
 
     // Return cart with items
@@ -61,6 +90,16 @@ pub async fn get_customer_cart(
 }
 
 /// Get cart by ID
+#[utoipa::path(
+    get,
+    path = "/carts/{cart_id}",
+    tag = "cart",
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 200, description = "The cart with its items", body = CartWithItems),
+        (status = 404, description = "Cart not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_cart(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
@@ -72,7 +111,7 @@ pub async fn get_cart(
 }
 
 /// Request body for adding item to cart
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct AddItemRequest {
     pub product_id: Uuid,
     pub variant_id: Option<Uuid>,
@@ -80,11 +119,30 @@ pub struct AddItemRequest {
 }
 
 /// Add item to cart
+#[utoipa::path(
+    post,
+    path = "/carts/{cart_id}/items",
+    tag = "cart",
+    request_body = AddItemRequest,
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 200, description = "The added (or increased) line", body = CartItem),
+        (status = 400, description = "Invalid quantity", body = ErrorResponse),
+        (status = 404, description = "Cart, product or variant not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn add_item_to_cart(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
     Json(request): Json<AddItemRequest>,
-) -> Result<Json<rcommerce_core::models::CartItem>, Error> {
+) -> Result<Json<CartItem>, Error> {
+    Ok(Json(add_line(&state, cart_id, &request).await?))
+}
+
+/// Add a product (or variant) to a cart at the customer's price; also
+/// used to reorder an earlier order's lines
+pub(crate) async fn add_line(state: &AppState, cart_id: Uuid, request: &AddItemRequest) -> Result<CartItem, Error> {
     // Validate quantity
     if request.quantity <= 0 {
         return Err(Error::validation("Quantity must be greater than 0"));
@@ -98,7 +156,7 @@ pub async fn add_item_to_cart(
         .ok_or_else(|| Error::not_found("Product not found"))?;
 
     // Build product details for cart service
-    let product_details = if let Some(variant_id) = request.variant_id {
+    let mut product_details = if let Some(variant_id) = request.variant_id {
         // Find the variant
         let variant = product_detail
             .variants
@@ -134,6 +192,33 @@ pub async fn add_item_to_cart(
         }
     };
 
+    // Members of a customer group pay its price for the line's new quantity
+    let cart = state.cart_service.get_cart_with_items(cart_id).await?;
+    if let Some(customer_id) = cart.cart.customer_id {
+        if let Some(pricing) = state
+            .customer_groups
+            .pricing_for_customer(customer_id, &[request.product_id])
+            .await?
+        {
+            let quantity = cart
+                .items
+                .iter()
+                .filter(|i| i.product_id == request.product_id && i.variant_id == request.variant_id)
+                .map(|i| i.quantity)
+                .sum::<i32>()
+                + request.quantity;
+            product_details.unit_price = OrderCalculator::new(Decimal::ZERO, Decimal::ZERO)
+                .with_customer_group(pricing)
+                .customer_unit_price(
+                    request.product_id,
+                    request.variant_id,
+                    quantity,
+                    product_details.unit_price,
+                    None,
+                );
+        }
+    }
+
     // Create input for adding to cart
     let input = AddToCartInput {
         product_id: request.product_id,
@@ -143,26 +228,37 @@ pub async fn add_item_to_cart(
     };
 
     // Add item to cart via service
-    let cart_item = state
+    state
         .cart_service
         .add_item(cart_id, input, product_details)
-        .await?;
-
-    Ok(Json(cart_item))
+        .await
 }
 
 /// Request body for updating cart item
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct UpdateItemRequest {
     pub quantity: i32,
 }
 
 /// Update cart item
+#[utoipa::path(
+    put,
+    path = "/carts/{cart_id}/items/{item_id}",
+    tag = "cart",
+    request_body = UpdateItemRequest,
+    params(("cart_id" = Uuid, Path, description = "Cart ID"), ("item_id" = Uuid, Path, description = "Cart item ID")),
+    responses(
+        (status = 200, description = "The updated line", body = CartItem),
+        (status = 400, description = "Invalid quantity", body = ErrorResponse),
+        (status = 404, description = "Cart item not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn update_cart_item(
     State(state): State<AppState>,
     Path((cart_id, item_id)): Path<(Uuid, Uuid)>,
     Json(request): Json<UpdateItemRequest>,
-) -> Result<Json<rcommerce_core::models::CartItem>, Error> {
+) -> Result<Json<CartItem>, Error> {
     // Validate quantity (0 is allowed - removes item)
     if request.quantity < 0 {
         return Err(Error::validation("Quantity cannot be negative"));
@@ -184,6 +280,17 @@ pub async fn update_cart_item(
 }
 
 /// Remove item from cart
+#[utoipa::path(
+    delete,
+    path = "/carts/{cart_id}/items/{item_id}",
+    tag = "cart",
+    params(("cart_id" = Uuid, Path, description = "Cart ID"), ("item_id" = Uuid, Path, description = "Cart item ID")),
+    responses(
+        (status = 204, description = "Line removed"),
+        (status = 404, description = "Cart item not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn remove_cart_item(
     State(state): State<AppState>,
     Path((cart_id, item_id)): Path<(Uuid, Uuid)>,
@@ -195,6 +302,17 @@ pub async fn remove_cart_item(
 }
 
 /// Clear all items from cart
+#[utoipa::path(
+    delete,
+    path = "/carts/{cart_id}/items",
+    tag = "cart",
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 204, description = "Cart emptied"),
+        (status = 404, description = "Cart not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn clear_cart(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
@@ -206,12 +324,23 @@ pub async fn clear_cart(
 }
 
 /// Request body for merging carts
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct MergeCartRequest {
     pub session_token: String,
 }
 
 /// Merge guest cart into customer cart
+#[utoipa::path(
+    post,
+    path = "/carts/merge",
+    tag = "cart",
+    request_body = MergeCartRequest,
+    responses(
+        (status = 200, description = "The customer's cart with the guest cart's items", body = CartWithItems),
+        (status = 404, description = "Guest cart not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn merge_carts(
     State(state): State<AppState>,
     Extension(jwt_auth): Extension<JwtAuth>,
@@ -230,12 +359,25 @@ pub async fn merge_carts(
 }
 
 /// Request body for applying coupon
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct ApplyCouponRequest {
     pub coupon_code: String,
 }
 
 /// Apply coupon to cart
+#[utoipa::path(
+    post,
+    path = "/carts/{cart_id}/coupon",
+    tag = "cart",
+    request_body = ApplyCouponRequest,
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 200, description = "The cart with the coupon applied", body = CartWithItems),
+        (status = 400, description = "Coupon is invalid or does not apply", body = ErrorResponse),
+        (status = 404, description = "Cart not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn apply_coupon(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
@@ -260,6 +402,17 @@ pub async fn apply_coupon(
 }
 
 /// Remove coupon from cart
+#[utoipa::path(
+    delete,
+    path = "/carts/{cart_id}/coupon",
+    tag = "cart",
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 200, description = "The cart without its coupon", body = CartWithItems),
+        (status = 404, description = "Cart not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn remove_coupon(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
@@ -274,6 +427,17 @@ pub async fn remove_coupon(
 }
 
 /// Delete cart
+#[utoipa::path(
+    delete,
+    path = "/carts/{cart_id}",
+    tag = "cart",
+    params(("cart_id" = Uuid, Path, description = "Cart ID")),
+    responses(
+        (status = 204, description = "Cart deleted"),
+        (status = 404, description = "Cart not found", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn delete_cart(
     State(state): State<AppState>,
     Path(cart_id): Path<Uuid>,
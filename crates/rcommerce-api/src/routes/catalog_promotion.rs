@@ -0,0 +1,59 @@
+//! Catalog Promotion API Routes
+//!
+//! Catalog snapshots and changes for promoting merchandising between
+//! instances (`rcommerce promote` fetches a snapshot from each and applies
+//! the approved changes to the target):
+//! - GET  /api/v1/admin/catalog/snapshot                   - This instance's catalog
+//! - POST /api/v1/admin/catalog/diff                       - Changes to make this instance match a snapshot
+//! - POST /api/v1/admin/catalog/changes                    - Apply approved changes
+
+use axum::{
+    extract::{DefaultBodyLimit, Extension, State},
+    routing::{get, post},
+    Json, Router,
+};
+use tracing::info;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    ApplyCatalogChangesRequest, CatalogApplyResult, CatalogChange, CatalogDiffRequest, CatalogSnapshot,
+};
+use rcommerce_core::Error;
+
+/// Snapshots of large catalogs exceed the default 2 MB body limit
+const CATALOG_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// GET /api/v1/admin/catalog/snapshot
+pub async fn get_snapshot(State(state): State<AppState>) -> Result<Json<CatalogSnapshot>, Error> {
+    Ok(Json(state.catalog_promotion.snapshot().await?))
+}
+
+/// POST /api/v1/admin/catalog/diff
+pub async fn diff_snapshot(
+    State(state): State<AppState>,
+    Json(request): Json<CatalogDiffRequest>,
+) -> Result<Json<Vec<CatalogChange>>, Error> {
+    Ok(Json(state.catalog_promotion.diff(&request.source, &request.options).await?))
+}
+
+/// POST /api/v1/admin/catalog/changes
+pub async fn apply_changes(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<ApplyCatalogChangesRequest>,
+) -> Result<Json<CatalogApplyResult>, Error> {
+    let count = request.changes.len();
+    let result = state.catalog_promotion.apply(request.changes).await?;
+    info!("Catalog promotion of {} changes applied by {}", count, auth.customer_id);
+    Ok(Json(result))
+}
+
+/// Admin router for catalog promotion
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/catalog/snapshot", get(get_snapshot))
+        .route("/admin/catalog/diff", post(diff_snapshot))
+        .route("/admin/catalog/changes", post(apply_changes))
+        .layer(DefaultBodyLimit::max(CATALOG_BODY_LIMIT))
+}
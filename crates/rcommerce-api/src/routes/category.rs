@@ -0,0 +1,140 @@
+//! Category API Routes
+//!
+//! Categories form a tree; `GET /api/v1/products?category=<id or slug>`
+//! lists a category's products with those of its subcategories:
+//! - GET    /api/v1/categories                                  - Tree of active categories
+//! - GET    /api/v1/categories/:id_or_slug                      - Get an active category
+//! - GET    /api/v1/admin/categories                            - Tree of all categories
+//! - POST   /api/v1/admin/categories                            - Create a category
+//! - GET    /api/v1/admin/categories/:id                        - Get a category
+//! - PUT    /api/v1/admin/categories/:id                        - Update (or move) a category
+//! - DELETE /api/v1/admin/categories/:id                        - Delete a category
+//! - PUT    /api/v1/admin/categories/:id/products               - Put products in a category
+//! - DELETE /api/v1/admin/categories/:id/products/:product_id   - Take a product out of a category
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::openapi::ErrorResponse;
+use crate::state::AppState;
+use rcommerce_core::models::{AssignProductsRequest, CreateCategoryRequest, ProductCategory, UpdateCategoryRequest};
+use rcommerce_core::repository::CategoryTreeNode;
+use rcommerce_core::Error;
+
+/// GET /api/v1/categories
+#[utoipa::path(
+    get,
+    path = "/categories",
+    tag = "categories",
+    summary = "Tree of active categories",
+    responses((status = 200, description = "Top-level categories with their subcategories", body = Vec<CategoryTreeNode>))
+)]
+pub async fn category_tree(State(state): State<AppState>) -> Result<Json<Vec<CategoryTreeNode>>, Error> {
+    Ok(Json(state.categories.category_tree(true).await?))
+}
+
+/// GET /api/v1/categories/:id_or_slug
+#[utoipa::path(
+    get,
+    path = "/categories/{id_or_slug}",
+    tag = "categories",
+    summary = "Get an active category",
+    params(("id_or_slug" = String, Path, description = "Category ID or slug")),
+    responses(
+        (status = 200, description = "The category", body = ProductCategory),
+        (status = 404, description = "Category not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_active_category(
+    State(state): State<AppState>,
+    Path(id_or_slug): Path<String>,
+) -> Result<Json<ProductCategory>, Error> {
+    let category = state.categories.resolve_category(&id_or_slug).await?;
+    if !category.is_active {
+        return Err(Error::not_found("Category not found"));
+    }
+    Ok(Json(category))
+}
+
+/// GET /api/v1/admin/categories
+pub async fn list_categories(State(state): State<AppState>) -> Result<Json<Vec<CategoryTreeNode>>, Error> {
+    Ok(Json(state.categories.category_tree(false).await?))
+}
+
+/// POST /api/v1/admin/categories
+pub async fn create_category(
+    State(state): State<AppState>,
+    Json(request): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<ProductCategory>), Error> {
+    let category = state.categories.create_category(request).await?;
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+/// GET /api/v1/admin/categories/:id
+pub async fn get_category(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProductCategory>, Error> {
+    Ok(Json(state.categories.get_category(id).await?))
+}
+
+/// PUT /api/v1/admin/categories/:id
+pub async fn update_category(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateCategoryRequest>,
+) -> Result<Json<ProductCategory>, Error> {
+    Ok(Json(state.categories.update_category(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/categories/:id
+pub async fn delete_category(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.categories.delete_category(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PUT /api/v1/admin/categories/:id/products
+pub async fn assign_products(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AssignProductsRequest>,
+) -> Result<StatusCode, Error> {
+    state.categories.assign_products(id, &request.product_ids).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/v1/admin/categories/:id/products/:product_id
+pub async fn remove_product(
+    State(state): State<AppState>,
+    Path((id, product_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.categories.remove_product(id, product_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for storefront category routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/categories", get(category_tree))
+        .route("/categories/:id_or_slug", get(get_active_category))
+}
+
+/// Router for category admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/categories", get(list_categories).post(create_category))
+        .route(
+            "/admin/categories/:id",
+            get(get_category).put(update_category).delete(delete_category),
+        )
+        .route("/admin/categories/:id/products", put(assign_products))
+        .route("/admin/categories/:id/products/:product_id", delete(remove_product))
+}
@@ -4,6 +4,18 @@
 //! - POST /checkout/initiate - Start checkout, calculate tax and shipping rates
 //! - POST /checkout/shipping - Select shipping method
 //! - POST /checkout/complete - Complete checkout, create order and process payment
+//!
+//! Add-ons chosen on the cart (`/carts/:cart_id/addons`) are included in the totals.
+//! Delivery instructions and a window from `/checkout/delivery-options` can
+//! be sent with the completed checkout, as can answers to the custom
+//! checkout fields listed by `/checkout/fields`. Gift card codes sent as
+//! `gift_cards` are spent first; `payment_method` is charged the rest.
+//! Flash sale items need the purchase token from
+//! `/flash-sales/:id/enter` in `flash_sale_tokens`.
+//! Orders shipping abroad are quoted duties and import taxes (`landed_cost`);
+//! `incoterm` picks DDP (prepaid, added to the total) or DAP (paid on delivery).
+//! With fraud screening on, risky orders are charged with 3-D Secure or held
+//! for review (`held_for_review`) until staff release them.
 
 use axum::{
     extract::State,
@@ -17,34 +29,42 @@ use uuid::Uuid;
 
 use crate::state::AppState;
 use crate::middleware::JwtAuth;
+use crate::middleware::geoip::ClientIp;
+use crate::openapi::CheckoutError;
 
 // Import core checkout types
 use rcommerce_core::services::{
     CheckoutSummary, CheckoutResult,
-    InitiateCheckoutRequest, SelectShippingRequest, CompleteCheckoutRequest,
+    InitiateCheckoutRequest, SelectShippingRequest, CompleteCheckoutRequest, GeoLocation,
 };
-use rcommerce_core::models::Address;
-use rcommerce_core::payment::{PaymentMethod, CardDetails};
+use rcommerce_core::models::{Address, CartAddon, CheckoutFieldValues};
+use rcommerce_core::payment::{PaymentMethod, CardDetails, GiftCardTender};
+use rcommerce_core::shipping::DeliveryDetails;
+use rcommerce_core::tax::{Incoterm, LandedCostQuote};
 
 /// Request to initiate checkout
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct InitiateCheckoutApiRequest {
     pub cart_id: Uuid,
     pub shipping_address: Address,
     pub billing_address: Option<Address>,
     pub vat_id: Option<String>,
     pub currency: Option<String>,
+    #[serde(default)]
+    pub incoterm: Option<Incoterm>,
 }
 
 /// Request to select shipping
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct SelectShippingApiRequest {
     pub cart_id: Uuid,
     pub shipping_rate: ShippingRateResponse,
+    #[serde(default)]
+    pub incoterm: Option<Incoterm>,
 }
 
 /// Request to complete checkout
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct CompleteCheckoutApiRequest {
     pub cart_id: Uuid,
     pub shipping_address: Address,
@@ -54,10 +74,21 @@ pub struct CompleteCheckoutApiRequest {
     pub vat_id: Option<String>,
     pub notes: Option<String>,
     pub selected_shipping_rate: ShippingRateResponse,
+    #[serde(default)]
+    pub delivery: Option<DeliveryDetails>,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub custom_fields: CheckoutFieldValues,
+    #[serde(default)]
+    pub gift_cards: Vec<String>,
+    #[serde(default)]
+    pub flash_sale_tokens: Vec<String>,
+    #[serde(default)]
+    pub incoterm: Option<Incoterm>,
 }
 
 /// Payment method request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PaymentMethodRequest {
     Card { 
@@ -98,7 +129,7 @@ impl From<PaymentMethodRequest> for PaymentMethod {
 }
 
 /// Shipping rate response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ShippingRateResponse {
     pub provider_id: String,
     pub carrier: String,
@@ -147,7 +178,7 @@ impl From<ShippingRateResponse> for rcommerce_core::shipping::ShippingRate {
 }
 
 /// Tax breakdown item
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct TaxBreakdownResponse {
     pub tax_zone_name: String,
     pub tax_rate_name: String,
@@ -157,7 +188,7 @@ pub struct TaxBreakdownResponse {
 }
 
 /// Cart item in checkout response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct CheckoutItemResponse {
     pub id: Uuid,
     pub product_id: Uuid,
@@ -170,11 +201,13 @@ pub struct CheckoutItemResponse {
 }
 
 /// Checkout summary response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct CheckoutSummaryResponse {
     pub cart_id: Uuid,
     pub items: Vec<CheckoutItemResponse>,
+    pub addons: Vec<CartAddon>,
     pub subtotal: Decimal,
+    pub addon_total: Decimal,
     pub discount_total: Decimal,
     pub shipping_total: Decimal,
     pub shipping_tax: Decimal,
@@ -186,6 +219,11 @@ pub struct CheckoutSummaryResponse {
     pub selected_shipping_rate: Option<ShippingRateResponse>,
     pub tax_breakdown: Vec<TaxBreakdownResponse>,
     pub vat_id_valid: Option<bool>,
+    /// Duties and import taxes of orders shipping abroad
+    pub landed_cost: Option<LandedCostQuote>,
+    pub incoterm: Option<Incoterm>,
+    /// Prepaid with DDP, included in `total`
+    pub landed_cost_total: Decimal,
 }
 
 impl From<CheckoutSummary> for CheckoutSummaryResponse {
@@ -202,7 +240,9 @@ impl From<CheckoutSummary> for CheckoutSummaryResponse {
                 unit_price: item.unit_price,
                 total: item.total,
             }).collect(),
+            addons: summary.addons,
             subtotal: summary.subtotal,
+            addon_total: summary.addon_total,
             discount_total: summary.discount_total,
             shipping_total: summary.shipping_total,
             shipping_tax: summary.shipping_tax,
@@ -220,12 +260,15 @@ impl From<CheckoutSummary> for CheckoutSummaryResponse {
                 tax_amount: tb.tax_amount,
             }).collect(),
             vat_id_valid: summary.vat_id_valid,
+            landed_cost: summary.landed_cost,
+            incoterm: summary.incoterm,
+            landed_cost_total: summary.landed_cost_total,
         }
     }
 }
 
 /// Order item response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct OrderItemResponse {
     pub id: Uuid,
     pub product_id: Uuid,
@@ -239,7 +282,7 @@ pub struct OrderItemResponse {
 }
 
 /// Order response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct OrderResponse {
     pub id: Uuid,
     pub order_number: String,
@@ -260,12 +303,18 @@ pub struct OrderResponse {
 }
 
 /// Checkout result response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct CheckoutResultResponse {
     pub order: OrderResponse,
-    pub payment_id: String,
+    /// None if gift cards paid for the whole order
+    pub payment_id: Option<String>,
     pub total_charged: Decimal,
     pub currency: String,
+    pub gift_cards: Vec<GiftCardTender>,
+    pub incoterm: Option<Incoterm>,
+    pub landed_cost_total: Decimal,
+    /// The order is on hold until staff review it for fraud
+    pub held_for_review: bool,
 }
 
 impl From<CheckoutResult> for CheckoutResultResponse {
@@ -292,6 +341,10 @@ impl From<CheckoutResult> for CheckoutResultResponse {
             payment_id: result.payment_id,
             total_charged: result.total_charged,
             currency: format!("{:?}", result.currency),
+            gift_cards: result.gift_cards,
+            incoterm: result.incoterm,
+            landed_cost_total: result.landed_cost_total,
+            held_for_review: result.held_for_review,
         }
     }
 }
@@ -299,14 +352,27 @@ impl From<CheckoutResult> for CheckoutResultResponse {
 /// Initiate checkout endpoint
 /// 
 /// Calculates totals, tax, and available shipping rates for the customer's cart
+#[utoipa::path(
+    post,
+    path = "/checkout/initiate",
+    tag = "checkout",
+    request_body = InitiateCheckoutApiRequest,
+    responses(
+        (status = 200, description = "Totals, taxes and shipping rates of the cart", body = CheckoutSummaryResponse),
+        (status = 400, description = "Checkout failed", body = CheckoutError),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn initiate_checkout(
     State(state): State<AppState>,
     Extension(auth): Extension<JwtAuth>,
+    location: Option<Extension<GeoLocation>>,
     Json(request): Json<InitiateCheckoutApiRequest>,
 ) -> Result<Json<CheckoutSummaryResponse>, (StatusCode, Json<serde_json::Value>)> {
-    // Build the core request
+    // Build the core request; the currency defaults to the client's location
     let currency = request.currency
         .and_then(|c| c.parse().ok())
+        .or_else(|| location.map(|Extension(location)| location.currency))
         .unwrap_or_default();
     
     let core_request = InitiateCheckoutRequest {
@@ -316,6 +382,7 @@ pub async fn initiate_checkout(
         vat_id: request.vat_id,
         customer_id: Some(auth.customer_id),
         currency: Some(currency),
+        incoterm: request.incoterm,
     };
 
     // Call checkout service
@@ -337,6 +404,17 @@ pub async fn initiate_checkout(
 /// Select shipping endpoint
 /// 
 /// Updates the checkout with the selected shipping method and recalculates totals
+#[utoipa::path(
+    post,
+    path = "/checkout/shipping",
+    tag = "checkout",
+    request_body = SelectShippingApiRequest,
+    responses(
+        (status = 200, description = "Totals with the selected shipping rate", body = CheckoutSummaryResponse),
+        (status = 400, description = "Checkout failed", body = CheckoutError),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn select_shipping(
     State(state): State<AppState>,
     Extension(_auth): Extension<JwtAuth>,
@@ -360,6 +438,7 @@ pub async fn select_shipping(
         cart_id: request.cart_id,
         shipping_rate,
         package,
+        incoterm: request.incoterm,
     };
 
     // Call checkout service
@@ -381,9 +460,21 @@ pub async fn select_shipping(
 /// Complete checkout endpoint
 /// 
 /// Finalizes the checkout, creates an order, and processes payment
+#[utoipa::path(
+    post,
+    path = "/checkout/complete",
+    tag = "checkout",
+    request_body = CompleteCheckoutApiRequest,
+    responses(
+        (status = 201, description = "Order placed and paid", body = CheckoutResultResponse),
+        (status = 400, description = "Checkout failed", body = CheckoutError),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn complete_checkout(
     State(state): State<AppState>,
     Extension(auth): Extension<JwtAuth>,
+    ClientIp(client_ip): ClientIp,
     Json(request): Json<CompleteCheckoutApiRequest>,
 ) -> Result<(StatusCode, Json<CheckoutResultResponse>), (StatusCode, Json<serde_json::Value>)> {
     // Validate email
@@ -405,6 +496,12 @@ pub async fn complete_checkout(
         vat_id: request.vat_id,
         notes: request.notes,
         selected_shipping_rate: request.selected_shipping_rate.into(),
+        delivery: request.delivery,
+        custom_fields: request.custom_fields,
+        gift_cards: request.gift_cards,
+        flash_sale_tokens: request.flash_sale_tokens,
+        incoterm: request.incoterm,
+        client_ip: client_ip.map(|ip| ip.to_string()),
     };
 
     // Call checkout service
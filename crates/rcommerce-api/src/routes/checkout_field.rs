@@ -0,0 +1,59 @@
+//! Custom Checkout Field API Routes
+//!
+//! Merchant-defined checkout fields (`[checkout_fields]` in the config):
+//! - GET /api/v1/checkout/fields                - Field schema for the storefront checkout form
+//! - GET /api/v1/admin/orders/:id/custom-fields - Answers recorded on an order
+//! - PUT /api/v1/admin/orders/:id/custom-fields - Correct an order's answers
+//!
+//! Answers are sent as `custom_fields` with `POST /checkout/complete`.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{CheckoutFieldDefinition, OrderCheckoutFields, UpdateCheckoutFieldsRequest};
+use rcommerce_core::Error;
+
+/// GET /api/v1/checkout/fields
+pub async fn get_checkout_fields(State(state): State<AppState>) -> Json<Vec<CheckoutFieldDefinition>> {
+    Json(state.checkout_fields.schema().fields().to_vec())
+}
+
+/// GET /api/v1/admin/orders/:id/custom-fields
+pub async fn get_order_custom_fields(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<OrderCheckoutFields>, Error> {
+    Ok(Json(state.checkout_fields.for_order(order_id).await?))
+}
+
+/// PUT /api/v1/admin/orders/:id/custom-fields
+pub async fn update_order_custom_fields(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<UpdateCheckoutFieldsRequest>,
+) -> Result<Json<OrderCheckoutFields>, Error> {
+    Ok(Json(
+        state
+            .checkout_fields
+            .update_for_order(order_id, request.custom_fields)
+            .await?,
+    ))
+}
+
+/// Router for storefront checkout field routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/checkout/fields", get(get_checkout_fields))
+}
+
+/// Router for checkout field admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route(
+        "/admin/orders/:id/custom-fields",
+        get(get_order_custom_fields).put(update_order_custom_fields),
+    )
+}
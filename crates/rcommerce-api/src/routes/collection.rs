@@ -0,0 +1,142 @@
+//! Collection API Routes
+//!
+//! Manual collections hold the products added to them; smart collections
+//! hold the products matching their tag, price and vendor rules.
+//! `GET /api/v1/products?collection=<id or handle>` lists a collection's
+//! products in its sort order:
+//! - GET    /api/v1/collections                                  - Published collections
+//! - GET    /api/v1/collections/:id_or_handle                    - Get a published collection
+//! - GET    /api/v1/admin/collections                            - All collections
+//! - POST   /api/v1/admin/collections                            - Create a collection
+//! - GET    /api/v1/admin/collections/:id                        - Get a collection
+//! - PUT    /api/v1/admin/collections/:id                        - Update a collection
+//! - DELETE /api/v1/admin/collections/:id                        - Delete a collection
+//! - POST   /api/v1/admin/collections/:id/products               - Add products to a manual collection
+//! - DELETE /api/v1/admin/collections/:id/products/:product_id   - Remove a product from a manual collection
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderName, StatusCode},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::conditional::last_modified;
+use crate::openapi::ErrorResponse;
+use crate::state::AppState;
+use rcommerce_core::models::{AssignProductsRequest, Collection, CreateCollectionRequest, UpdateCollectionRequest};
+use rcommerce_core::Error;
+
+/// GET /api/v1/collections
+#[utoipa::path(
+    get,
+    path = "/collections",
+    tag = "collections",
+    summary = "Published collections",
+    responses((status = 200, description = "Published collections", body = Vec<Collection>))
+)]
+pub async fn list_published_collections(State(state): State<AppState>) -> Result<Json<Vec<Collection>>, Error> {
+    Ok(Json(state.collections.list_collections(true).await?))
+}
+
+/// GET /api/v1/collections/:id_or_handle
+#[utoipa::path(
+    get,
+    path = "/collections/{id_or_handle}",
+    tag = "collections",
+    summary = "Get a published collection",
+    params(("id_or_handle" = String, Path, description = "Collection ID or handle")),
+    responses(
+        (status = 200, description = "The collection", body = Collection),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_published_collection(
+    State(state): State<AppState>,
+    Path(id_or_handle): Path<String>,
+) -> Result<([(HeaderName, String); 1], Json<Collection>), Error> {
+    let collection = state.collections.resolve_collection(&id_or_handle).await?;
+    if !collection.is_published() {
+        return Err(Error::not_found("Collection not found"));
+    }
+    Ok((last_modified(collection.updated_at), Json(collection)))
+}
+
+/// GET /api/v1/admin/collections
+pub async fn list_collections(State(state): State<AppState>) -> Result<Json<Vec<Collection>>, Error> {
+    Ok(Json(state.collections.list_collections(false).await?))
+}
+
+/// POST /api/v1/admin/collections
+pub async fn create_collection(
+    State(state): State<AppState>,
+    Json(request): Json<CreateCollectionRequest>,
+) -> Result<(StatusCode, Json<Collection>), Error> {
+    let collection = state.collections.create_collection(request).await?;
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+/// GET /api/v1/admin/collections/:id
+pub async fn get_collection(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Collection>, Error> {
+    Ok(Json(state.collections.get_collection(id).await?))
+}
+
+/// PUT /api/v1/admin/collections/:id
+pub async fn update_collection(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateCollectionRequest>,
+) -> Result<Json<Collection>, Error> {
+    Ok(Json(state.collections.update_collection(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/collections/:id
+pub async fn delete_collection(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.collections.delete_collection(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/collections/:id/products
+pub async fn add_products(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AssignProductsRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let added = state.collections.add_products(id, &request.product_ids).await?;
+    Ok(Json(serde_json::json!({ "added": added })))
+}
+
+/// DELETE /api/v1/admin/collections/:id/products/:product_id
+pub async fn remove_product(
+    State(state): State<AppState>,
+    Path((id, product_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.collections.remove_product(id, product_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for storefront collection routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/collections", get(list_published_collections))
+        .route("/collections/:id_or_handle", get(get_published_collection))
+}
+
+/// Router for collection admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/collections", get(list_collections).post(create_collection))
+        .route(
+            "/admin/collections/:id",
+            get(get_collection).put(update_collection).delete(delete_collection),
+        )
+        .route("/admin/collections/:id/products", post(add_products))
+        .route("/admin/collections/:id/products/:product_id", delete(remove_product))
+}
@@ -1,47 +1,67 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::get,
     Extension, Json, Router,
 };
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::middleware::JwtAuth;
 use crate::state::AppState;
+use rcommerce_core::models::{Customer, Cursor};
 use rcommerce_core::{services::PaginationParams, Error};
 
+/// Most customers listed per page
+const PER_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CustomerListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// `next_cursor` of the previous page; empty for the first
+    pub cursor: Option<String>,
+}
+
 /// List customers (admin only)
 pub async fn list_customers(
     State(state): State<AppState>,
     Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<CustomerListQuery>,
 ) -> Result<Json<serde_json::Value>, Error> {
     // Check admin permission
     if !auth.is_admin() {
         return Err(Error::unauthorized("Admin access required"));
     }
 
+    let default = PaginationParams::default();
+    let pagination = PaginationParams {
+        page: query.page.unwrap_or(default.page).max(1),
+        per_page: query.per_page.unwrap_or(default.per_page).clamp(1, PER_PAGE_LIMIT),
+    };
+    if query.cursor.is_some() {
+        let cursor = Cursor::parse(query.cursor.as_deref())?;
+        let page = state
+            .customer_service
+            .list_customers_after(cursor.as_ref(), pagination.per_page)
+            .await?;
+        return Ok(Json(serde_json::json!({
+            "customers": page.items.into_iter().map(customer_json).collect::<Vec<_>>(),
+            "meta": {
+                "per_page": pagination.per_page,
+                "next_cursor": page.next_cursor,
+            }
+        })));
+    }
+
     let customer_list = state
         .customer_service
-        .list_customers(PaginationParams::default())
+        .list_customers(pagination)
         .await?;
 
     let customers: Vec<serde_json::Value> = customer_list
         .customers
         .into_iter()
-        .map(|c| {
-            serde_json::json!({
-                "id": c.id,
-                "email": c.email,
-                "first_name": c.first_name,
-                "last_name": c.last_name,
-                "phone": c.phone,
-                "accepts_marketing": c.accepts_marketing,
-                "tax_exempt": c.tax_exempt,
-                "currency": c.currency.to_string(),
-                "created_at": c.created_at,
-                "updated_at": c.updated_at,
-                "confirmed_at": c.confirmed_at,
-            })
-        })
+        .map(customer_json)
         .collect();
 
     Ok(Json(serde_json::json!({
@@ -55,6 +75,23 @@ pub async fn list_customers(
     })))
 }
 
+/// A customer as listed
+fn customer_json(c: Customer) -> serde_json::Value {
+    serde_json::json!({
+        "id": c.id,
+        "email": c.email,
+        "first_name": c.first_name,
+        "last_name": c.last_name,
+        "phone": c.phone,
+        "accepts_marketing": c.accepts_marketing,
+        "tax_exempt": c.tax_exempt,
+        "currency": c.currency.to_string(),
+        "created_at": c.created_at,
+        "updated_at": c.updated_at,
+        "confirmed_at": c.confirmed_at,
+    })
+}
+
 /// Get customer by ID
 pub async fn get_customer(
     State(state): State<AppState>,
@@ -0,0 +1,124 @@
+//! Customer Group API Routes
+//!
+//! Groups (wholesale, VIP, retail, ...) with a percentage discount and
+//! their own product prices in quantity tiers. Members see the group's
+//! prices from `GET /api/v1/products/:id/price` and pay them in carts and
+//! orders:
+//! - GET    /api/v1/admin/customer-groups                          - List customer groups
+//! - POST   /api/v1/admin/customer-groups                          - Create a customer group
+//! - GET    /api/v1/admin/customer-groups/:id                      - Get a customer group
+//! - PUT    /api/v1/admin/customer-groups/:id                      - Update a customer group
+//! - DELETE /api/v1/admin/customer-groups/:id                      - Delete a customer group
+//! - GET    /api/v1/admin/customer-groups/:id/prices               - The group's prices
+//! - PUT    /api/v1/admin/customer-groups/:id/prices               - Set a product or variant price tier
+//! - DELETE /api/v1/admin/customer-groups/:id/prices/:price_id     - Remove a price
+//! - PUT    /api/v1/admin/customers/:id/customer-group             - Move a customer into (or out of) a group
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AssignCustomerGroupRequest, CreateCustomerGroupRequest, CustomerGroup, CustomerGroupPrice,
+    SetCustomerGroupPriceRequest, UpdateCustomerGroupRequest,
+};
+use rcommerce_core::repository::CustomerGroupRepository;
+use rcommerce_core::Error;
+
+/// GET /api/v1/admin/customer-groups
+pub async fn list_groups(State(state): State<AppState>) -> Result<Json<Vec<CustomerGroup>>, Error> {
+    Ok(Json(state.customer_groups.repository().list().await?))
+}
+
+/// POST /api/v1/admin/customer-groups
+pub async fn create_group(
+    State(state): State<AppState>,
+    Json(request): Json<CreateCustomerGroupRequest>,
+) -> Result<(StatusCode, Json<CustomerGroup>), Error> {
+    let group = state.customer_groups.create_group(request).await?;
+    Ok((StatusCode::CREATED, Json(group)))
+}
+
+/// GET /api/v1/admin/customer-groups/:id
+pub async fn get_group(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CustomerGroup>, Error> {
+    Ok(Json(state.customer_groups.get_group(id).await?))
+}
+
+/// PUT /api/v1/admin/customer-groups/:id
+pub async fn update_group(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateCustomerGroupRequest>,
+) -> Result<Json<CustomerGroup>, Error> {
+    Ok(Json(state.customer_groups.update_group(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/customer-groups/:id
+pub async fn delete_group(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.customer_groups.delete_group(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/customer-groups/:id/prices
+pub async fn list_prices(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<CustomerGroupPrice>>, Error> {
+    state.customer_groups.get_group(id).await?;
+    Ok(Json(state.customer_groups.repository().prices(id).await?))
+}
+
+/// PUT /api/v1/admin/customer-groups/:id/prices
+pub async fn set_price(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetCustomerGroupPriceRequest>,
+) -> Result<Json<CustomerGroupPrice>, Error> {
+    Ok(Json(state.customer_groups.set_price(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/customer-groups/:id/prices/:price_id
+pub async fn remove_price(
+    State(state): State<AppState>,
+    Path((id, price_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.customer_groups.remove_price(id, price_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PUT /api/v1/admin/customers/:id/customer-group
+pub async fn assign_group(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Json(request): Json<AssignCustomerGroupRequest>,
+) -> Result<StatusCode, Error> {
+    state
+        .customer_groups
+        .assign(customer_id, request.customer_group_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for customer group admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/customer-groups", get(list_groups).post(create_group))
+        .route(
+            "/admin/customer-groups/:id",
+            get(get_group).put(update_group).delete(delete_group),
+        )
+        .route("/admin/customer-groups/:id/prices", get(list_prices).put(set_price))
+        .route("/admin/customer-groups/:id/prices/:price_id", delete(remove_price))
+        .route("/admin/customers/:id/customer-group", put(assign_group))
+}
@@ -0,0 +1,64 @@
+//! Product Customs API Routes
+//!
+//! The HS code and country of origin of products. Checkout uses them to
+//! quote duties of orders shipping abroad (`[landed_cost]`), and labels for
+//! parcels crossing a border declare them to customs:
+//! - GET    /api/v1/admin/products/:id/customs  - Get a product's customs data
+//! - PUT    /api/v1/admin/products/:id/customs  - Set a product's customs data
+//! - DELETE /api/v1/admin/products/:id/customs  - Clear a product's customs data
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::state::AppState;
+use rcommerce_core::models::{ProductCustoms, SetProductCustomsRequest};
+use rcommerce_core::repository::CustomsRepository;
+use rcommerce_core::Error;
+
+/// GET /api/v1/admin/products/:id/customs
+pub async fn get_customs(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<ProductCustoms>, Error> {
+    let customs = state
+        .customs
+        .find(product_id)
+        .await?
+        .ok_or_else(|| Error::not_found("Product has no customs data"))?;
+    Ok(Json(customs))
+}
+
+/// PUT /api/v1/admin/products/:id/customs
+pub async fn set_customs(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<SetProductCustomsRequest>,
+) -> Result<Json<ProductCustoms>, Error> {
+    request.validate().map_err(|e| Error::validation(e.to_string()))?;
+    Ok(Json(state.customs.upsert(product_id, &request).await?))
+}
+
+/// DELETE /api/v1/admin/products/:id/customs
+pub async fn delete_customs(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    if !state.customs.delete(product_id).await? {
+        return Err(Error::not_found("Product has no customs data"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Admin router for product customs data
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route(
+        "/admin/products/:id/customs",
+        get(get_customs).put(set_customs).delete(delete_customs),
+    )
+}
@@ -0,0 +1,70 @@
+//! Delivery API Routes
+//!
+//! Delivery instructions and scheduled delivery windows:
+//! - GET /api/v1/checkout/delivery-options        - Bookable dates and windows for a carrier service
+//! - GET /api/v1/admin/orders/:id/delivery        - Delivery preferences chosen for an order
+//! - GET /api/v1/admin/orders/:id/packing-slip    - Printable packing slip (text/plain)
+//!
+//! Chosen preferences are sent as `delivery` with `POST /checkout/complete`.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::shipping::{DeliveryDetails, DeliverySchedule};
+use rcommerce_core::Error;
+
+/// Carrier service to offer delivery options for
+#[derive(Debug, Deserialize)]
+pub struct DeliveryOptionsQuery {
+    pub provider_id: String,
+    pub service_code: String,
+}
+
+/// GET /api/v1/checkout/delivery-options
+pub async fn get_delivery_options(
+    State(state): State<AppState>,
+    Query(query): Query<DeliveryOptionsQuery>,
+) -> Json<DeliverySchedule> {
+    Json(state.delivery.options(&query.provider_id, &query.service_code))
+}
+
+/// GET /api/v1/admin/orders/:id/delivery
+pub async fn get_order_delivery(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<DeliveryDetails>, Error> {
+    Ok(Json(state.delivery.for_order(order_id).await?))
+}
+
+/// GET /api/v1/admin/orders/:id/packing-slip
+pub async fn get_packing_slip(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let pick_list = state
+        .addons
+        .pick_list(order_id)
+        .await?
+        .with_delivery(state.delivery.for_order(order_id).await?);
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], pick_list.packing_slip()))
+}
+
+/// Router for storefront delivery routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/checkout/delivery-options", get(get_delivery_options))
+}
+
+/// Router for delivery admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/orders/:id/delivery", get(get_order_delivery))
+        .route("/admin/orders/:id/packing-slip", get(get_packing_slip))
+}
@@ -0,0 +1,124 @@
+//! Email Provider Event Routes
+//!
+//! Public webhooks that feed bounces, complaints and deliveries back into
+//! notification delivery statuses:
+//! - POST /api/v1/email/events/sendgrid - SendGrid Event Webhook (signature
+//!   checked when `sendgrid.webhook_public_key` is set)
+//! - POST /api/v1/email/events/ses?token=... - SNS subscription for SES
+//!   events; the token must match `ses.webhook_token`
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::HeaderMap,
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::state::AppState;
+use rcommerce_core::notification::channels::{sendgrid, ses, EmailEvent};
+use rcommerce_core::repository::NotificationRepository;
+use rcommerce_core::Error;
+
+/// Outcome of an event delivery
+#[derive(Debug, Serialize)]
+pub struct EmailEventsResponse {
+    /// Events that change a delivery status
+    pub received: usize,
+    /// Notifications whose status was updated
+    pub updated: usize,
+}
+
+async fn apply_events(state: &AppState, provider: &str, events: Vec<EmailEvent>) -> Result<EmailEventsResponse, Error> {
+    let mut updated = 0;
+    for event in &events {
+        if state.notifications.record_provider_event(event).await? {
+            updated += 1;
+        } else {
+            info!(
+                "No notification updated for {} {:?} event to {} (message {:?})",
+                provider, event.status, event.recipient, event.message_id
+            );
+        }
+    }
+    Ok(EmailEventsResponse { received: events.len(), updated })
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// POST /api/v1/email/events/sendgrid
+pub async fn sendgrid_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<EmailEventsResponse>, Error> {
+    let config = state
+        .email
+        .sendgrid
+        .as_ref()
+        .ok_or_else(|| Error::not_found("SendGrid is not configured"))?;
+
+    if let Some(ref public_key) = config.webhook_public_key {
+        let (Some(signature), Some(timestamp)) = (
+            header(&headers, sendgrid::SIGNATURE_HEADER),
+            header(&headers, sendgrid::TIMESTAMP_HEADER),
+        ) else {
+            return Err(Error::unauthorized("Missing SendGrid webhook signature"));
+        };
+        sendgrid::verify_signature(public_key, signature, timestamp, &body)?;
+    }
+
+    let events = sendgrid::parse_events(&body)?;
+    Ok(Json(apply_events(&state, "sendgrid", events).await?))
+}
+
+/// Query parameters of the SNS subscription URL
+#[derive(Debug, Deserialize)]
+pub struct SesEventsQuery {
+    pub token: Option<String>,
+}
+
+/// POST /api/v1/email/events/ses
+pub async fn ses_events(
+    State(state): State<AppState>,
+    Query(query): Query<SesEventsQuery>,
+    body: Bytes,
+) -> Result<Json<EmailEventsResponse>, Error> {
+    let expected = state
+        .email
+        .ses
+        .as_ref()
+        .and_then(|ses| ses.webhook_token.as_deref())
+        .ok_or_else(|| Error::not_found("SES event webhook is not configured"))?;
+    if query.token.as_deref() != Some(expected) {
+        return Err(Error::unauthorized("Invalid SES webhook token"));
+    }
+
+    match ses::parse_sns(&body)? {
+        ses::SnsMessage::SubscriptionConfirmation { topic_arn, subscribe_url } => {
+            if !ses::is_sns_url(&subscribe_url) {
+                warn!("Ignoring SNS subscription confirmation with unexpected URL {}", subscribe_url);
+                return Err(Error::validation("SubscribeURL is not an SNS endpoint"));
+            }
+            reqwest::get(&subscribe_url)
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| Error::network(format!("Failed to confirm SNS subscription: {}", e)))?;
+            info!("Confirmed SNS subscription to {}", topic_arn);
+            Ok(Json(EmailEventsResponse { received: 0, updated: 0 }))
+        }
+        ses::SnsMessage::Notification(events) => Ok(Json(apply_events(&state, "ses", events).await?)),
+        ses::SnsMessage::Other => Ok(Json(EmailEventsResponse { received: 0, updated: 0 })),
+    }
+}
+
+/// Router for email provider webhooks (public)
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/email/events/sendgrid", post(sendgrid_events))
+        .route("/email/events/ses", post(ses_events))
+}
@@ -0,0 +1,58 @@
+//! Event Schema API Routes
+//!
+//! - GET /api/v1/admin/events/schema - JSON Schemas of domain events and webhook payloads
+//!
+//! `?version=N` pins the catalog version an integration was built against:
+//! the request fails with 404 once the payloads have changed incompatibly.
+//! The `ETag` is the catalog's fingerprint.
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::event_schema::{catalog, catalog_json, EVENT_SCHEMA_VERSION};
+use crate::state::AppState;
+use rcommerce_core::Error;
+
+/// Query parameters for the event schema catalog
+#[derive(Debug, Deserialize)]
+pub struct EventSchemaQuery {
+    pub version: Option<u32>,
+}
+
+/// GET /api/v1/admin/events/schema
+pub async fn get_event_schema(
+    Query(query): Query<EventSchemaQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    if let Some(version) = query.version.filter(|version| *version != EVENT_SCHEMA_VERSION) {
+        return Err(Error::not_found(format!(
+            "Event schema version {} is not available (current: {})",
+            version, EVENT_SCHEMA_VERSION
+        )));
+    }
+
+    let etag = format!("\"{}\"", catalog()["fingerprint"].as_str().unwrap_or_default());
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == etag)
+    {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    Ok((
+        [(header::CONTENT_TYPE, "application/schema+json".to_string()), (header::ETAG, etag)],
+        catalog_json(),
+    )
+        .into_response())
+}
+
+/// Admin router for the event schema catalog
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route("/admin/events/schema", get(get_event_schema))
+}
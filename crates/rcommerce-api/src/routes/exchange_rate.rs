@@ -0,0 +1,104 @@
+//! Exchange Rate API Routes
+//!
+//! Admin-only endpoints for the historical exchange rate table:
+//! - GET  /api/v1/admin/exchange-rates    - Rate history (filter by pair and date range)
+//! - POST /api/v1/admin/exchange-rates    - Record a new rate
+//! - GET  /api/v1/admin/exchange-rates/at - Rate in effect at a point in time
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::models::{Currency, ExchangeRate, ExchangeRateFilter, RecordExchangeRateRequest};
+use rcommerce_core::repository::{ExchangeRateRepository, PostgresExchangeRateRepository};
+
+/// Query parameters for rate history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub base: Option<Currency>,
+    pub quote: Option<Currency>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+/// Query parameters for a point-in-time lookup
+#[derive(Debug, Deserialize)]
+pub struct RateAtQuery {
+    pub base: Currency,
+    pub quote: Currency,
+    /// Defaults to now
+    pub at: Option<DateTime<Utc>>,
+}
+
+fn repository(state: &AppState) -> PostgresExchangeRateRepository {
+    PostgresExchangeRateRepository::new(state.db.pool().clone())
+}
+
+/// GET /api/v1/admin/exchange-rates
+pub async fn list_exchange_rates(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<ExchangeRate>>, StatusCode> {
+    let filter = ExchangeRateFilter {
+        base_currency: query.base,
+        quote_currency: query.quote,
+        from: query.from,
+        to: query.to,
+    };
+
+    repository(&state)
+        .history(&filter, query.limit.clamp(1, 1000), query.offset.max(0))
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to list exchange rates: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// POST /api/v1/admin/exchange-rates
+pub async fn record_exchange_rate(
+    State(state): State<AppState>,
+    Json(request): Json<RecordExchangeRateRequest>,
+) -> Result<(StatusCode, Json<ExchangeRate>), rcommerce_core::Error> {
+    let rate = repository(&state).record(request).await?;
+    Ok((StatusCode::CREATED, Json(rate)))
+}
+
+/// GET /api/v1/admin/exchange-rates/at
+pub async fn get_rate_at(
+    State(state): State<AppState>,
+    Query(query): Query<RateAtQuery>,
+) -> Result<Json<ExchangeRate>, StatusCode> {
+    let at = query.at.unwrap_or_else(Utc::now);
+
+    match repository(&state).rate_at(query.base, query.quote, at).await {
+        Ok(Some(rate)) => Ok(Json(rate)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch exchange rate: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Router for exchange rate routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/exchange-rates", get(list_exchange_rates).post(record_exchange_rate))
+        .route("/admin/exchange-rates/at", get(get_rate_at))
+}
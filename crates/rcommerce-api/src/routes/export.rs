@@ -0,0 +1,98 @@
+//! Data export routes
+//!
+//! GET /api/v1/export/:entity - Download products, customers or orders
+//!
+//! Query parameters: `format` (csv or json, default csv), `since` and
+//! `until` (RFC 3339, on the creation date) and `status` (products:
+//! active/inactive, customers: verified/unverified, orders: order status).
+//! The body is streamed as rows are read, so large exports start at once
+//! and do not build up in memory. Needs the `exports:read` permission.
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+use crate::state::AppState;
+use rcommerce_core::models::{ExportEntity, ExportFilter, ExportFormat};
+use rcommerce_core::Error;
+
+/// Encoded chunks buffered ahead of a slow client
+const BUFFERED_CHUNKS: usize = 64;
+
+/// Query parameters for an export
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+}
+
+/// GET /api/v1/export/:entity
+pub async fn export(
+    State(state): State<AppState>,
+    Path(entity): Path<ExportEntity>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, Error> {
+    let filter = ExportFilter {
+        since: query.since,
+        until: query.until,
+        status: query.status,
+    };
+    state.exports.validate(entity, &filter)?;
+
+    // The body must own its stream, so encode in a task that feeds a bounded channel
+    let (tx, rx) = tokio::sync::mpsc::channel(BUFFERED_CHUNKS);
+    let exports = state.exports.clone();
+    let format = query.format;
+    tokio::spawn(async move {
+        let mut chunks = match exports.stream(entity, format, &filter) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+        while let Some(chunk) = chunks.next().await {
+            if let Err(e) = &chunk {
+                tracing::error!("Export of {} failed: {}", entity, e);
+            }
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let body = Body::from_stream(
+        ReceiverStream::new(rx).map(|chunk| chunk.map_err(std::io::Error::other)),
+    );
+    let filename = format!(
+        "attachment; filename=\"{}-{}.{}\"",
+        entity,
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Router for data export routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/export/:entity", get(export))
+}
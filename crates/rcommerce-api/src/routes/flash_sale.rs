@@ -0,0 +1,124 @@
+//! Flash Sale API Routes
+//!
+//! A live flash sale is served from Redis: customers poll its waiting room
+//! until they are admitted with a purchase token, then send the token as
+//! `flash_sale_tokens` with `POST /checkout/complete`. Waiting responses
+//! carry a `Retry-After` header.
+//! - GET  /api/v1/flash-sales/:id                 - Units left and queue length of a live sale
+//! - POST /api/v1/flash-sales/:id/enter           - Enter the waiting room (or check your place)
+//! - GET  /api/v1/admin/flash-sales               - Sales (`?status=live`)
+//! - POST /api/v1/admin/flash-sales               - Define a draft sale
+//! - GET  /api/v1/admin/flash-sales/:id           - Sale with its live counters
+//! - POST /api/v1/admin/flash-sales/:id/start     - Put a draft sale live
+//! - POST /api/v1/admin/flash-sales/:id/close     - Close a live sale and record what sold
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreateFlashSaleRequest, FlashSale, FlashSaleAdmission, FlashSaleDetails, FlashSaleStats, FlashSaleStatus,
+};
+use rcommerce_core::Error;
+
+/// Flash sales listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing flash sales
+#[derive(Debug, Deserialize)]
+pub struct ListFlashSalesQuery {
+    pub status: Option<FlashSaleStatus>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/flash-sales/:id
+pub async fn get_flash_sale_stats(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FlashSaleStats>, Error> {
+    Ok(Json(state.flash_sales.stats(id).await?))
+}
+
+/// POST /api/v1/flash-sales/:id/enter
+pub async fn enter_flash_sale(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Response, Error> {
+    let admission = state.flash_sales.enter(id, auth.customer_id).await?;
+    Ok(match admission {
+        FlashSaleAdmission::Waiting { retry_after_secs, .. } => (
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(admission),
+        )
+            .into_response(),
+        admission => Json(admission).into_response(),
+    })
+}
+
+/// GET /api/v1/admin/flash-sales
+pub async fn list_flash_sales(
+    State(state): State<AppState>,
+    Query(query): Query<ListFlashSalesQuery>,
+) -> Result<Json<Vec<FlashSale>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    Ok(Json(state.flash_sales.list(query.status, limit).await?))
+}
+
+/// POST /api/v1/admin/flash-sales
+pub async fn create_flash_sale(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateFlashSaleRequest>,
+) -> Result<(StatusCode, Json<FlashSale>), Error> {
+    let sale = state.flash_sales.create(request, Some(auth.customer_id)).await?;
+    Ok((StatusCode::CREATED, Json(sale)))
+}
+
+/// GET /api/v1/admin/flash-sales/:id
+pub async fn get_flash_sale(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FlashSaleDetails>, Error> {
+    Ok(Json(state.flash_sales.get_details(id).await?))
+}
+
+/// POST /api/v1/admin/flash-sales/:id/start
+pub async fn start_flash_sale(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FlashSale>, Error> {
+    Ok(Json(state.flash_sales.start(id).await?))
+}
+
+/// POST /api/v1/admin/flash-sales/:id/close
+pub async fn close_flash_sale(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FlashSale>, Error> {
+    Ok(Json(state.flash_sales.close(id).await?))
+}
+
+/// Router for storefront flash sale routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/flash-sales/:id", get(get_flash_sale_stats))
+        .route("/flash-sales/:id/enter", post(enter_flash_sale))
+}
+
+/// Router for flash sale admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/flash-sales", get(list_flash_sales).post(create_flash_sale))
+        .route("/admin/flash-sales/:id", get(get_flash_sale))
+        .route("/admin/flash-sales/:id/start", post(start_flash_sale))
+        .route("/admin/flash-sales/:id/close", post(close_flash_sale))
+}
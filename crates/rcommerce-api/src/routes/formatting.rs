@@ -0,0 +1,102 @@
+//! Price Formatting API Routes
+//!
+//! Public display rules so storefronts format prices the same way as
+//! emails and invoices:
+//! - GET /api/v1/formatting      - All locales and currencies
+//! - GET /api/v1/formatting/rule - Resolved rule for a locale and currency
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    routing::get,
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::state::AppState;
+use rcommerce_core::services::formatting_service::{CurrencyFormat, LocaleFormat};
+use rcommerce_core::services::PriceDisplayRule;
+
+/// All display rules
+#[derive(Debug, Serialize)]
+pub struct FormattingConfigResponse {
+    pub default_locale: String,
+    pub locales: Vec<LocaleFormat>,
+    pub currencies: Vec<CurrencyFormat>,
+}
+
+/// Query parameters for a rule lookup
+#[derive(Debug, Deserialize)]
+pub struct RuleQuery {
+    /// Falls back to the Accept-Language header, then the store default
+    pub locale: Option<String>,
+    pub currency: String,
+}
+
+/// Resolved rule with a formatted example
+#[derive(Debug, Serialize)]
+pub struct RuleResponse {
+    #[serde(flatten)]
+    pub rule: PriceDisplayRule,
+    pub example: String,
+}
+
+/// First language tag from an Accept-Language header
+pub fn accept_language(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|tag| tag.split(';').next().unwrap_or_default().trim().to_string())
+        .filter(|tag| !tag.is_empty() && tag != "*")
+}
+
+/// GET /api/v1/formatting
+pub async fn get_formatting_config(State(state): State<AppState>) -> Json<FormattingConfigResponse> {
+    let formatting = &state.formatting;
+    Json(FormattingConfigResponse {
+        default_locale: formatting.default_locale().to_string(),
+        locales: formatting.locales().into_iter().cloned().collect(),
+        currencies: formatting.currencies().into_iter().cloned().collect(),
+    })
+}
+
+/// GET /api/v1/formatting/rule
+pub async fn get_formatting_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<RuleQuery>,
+) -> Json<RuleResponse> {
+    let locale = query.locale.or_else(|| accept_language(&headers));
+    let rule = state.formatting.rule(locale.as_deref(), &query.currency);
+
+    Json(RuleResponse {
+        example: rule.format(Decimal::new(123456, 2)),
+        rule,
+    })
+}
+
+/// Router for formatting routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/formatting", get(get_formatting_config))
+        .route("/formatting/rule", get(get_formatting_rule))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(accept_language(&headers), None);
+
+        headers.insert(header::ACCEPT_LANGUAGE, "de-DE,de;q=0.9,en;q=0.8".parse().unwrap());
+        assert_eq!(accept_language(&headers).as_deref(), Some("de-DE"));
+
+        headers.insert(header::ACCEPT_LANGUAGE, "*".parse().unwrap());
+        assert_eq!(accept_language(&headers), None);
+    }
+}
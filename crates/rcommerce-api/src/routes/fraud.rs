@@ -0,0 +1,82 @@
+//! Fraud Review API Routes
+//!
+//! With `[fraud]` screening on, checkout scores each order; orders over
+//! `review_score` are held (`on_hold`) until staff review them. Rejecting
+//! cancels the order; a paid one is refunded with
+//! `POST /api/v1/admin/payments/:id/refund`:
+//! - GET  /api/v1/admin/fraud/reviews                     - Review queue, oldest first (`?status=pending`)
+//! - POST /api/v1/admin/fraud/reviews/:order_id/approve   - Release a held order
+//! - POST /api/v1/admin/fraud/reviews/:order_id/reject    - Cancel a held order
+//! - GET  /api/v1/admin/orders/:id/risk                   - Risk score and signals of an order
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{RiskAssessment, RiskReviewItem, RiskReviewRequest, RiskReviewStatus};
+use rcommerce_core::Error;
+
+/// Queue entries listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters for the review queue
+#[derive(Debug, Deserialize)]
+pub struct ReviewQueueQuery {
+    pub status: Option<RiskReviewStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/fraud/reviews
+pub async fn review_queue(
+    State(state): State<AppState>,
+    Query(query): Query<ReviewQueueQuery>,
+) -> Result<Json<Vec<RiskReviewItem>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let status = query.status.unwrap_or(RiskReviewStatus::Pending);
+    Ok(Json(state.fraud.review_queue(status, limit, offset).await?))
+}
+
+/// POST /api/v1/admin/fraud/reviews/:order_id/approve
+pub async fn approve_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<RiskReviewRequest>,
+) -> Result<Json<RiskAssessment>, Error> {
+    Ok(Json(state.fraud.approve(order_id, auth.customer_id, request).await?))
+}
+
+/// POST /api/v1/admin/fraud/reviews/:order_id/reject
+pub async fn reject_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<RiskReviewRequest>,
+) -> Result<Json<RiskAssessment>, Error> {
+    Ok(Json(state.fraud.reject(order_id, auth.customer_id, request).await?))
+}
+
+/// GET /api/v1/admin/orders/:id/risk
+pub async fn order_risk(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<RiskAssessment>, Error> {
+    Ok(Json(state.fraud.get(order_id).await?))
+}
+
+/// Admin router for fraud review
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/fraud/reviews", get(review_queue))
+        .route("/admin/fraud/reviews/:order_id/approve", post(approve_order))
+        .route("/admin/fraud/reviews/:order_id/reject", post(reject_order))
+        .route("/admin/orders/:id/risk", get(order_risk))
+}
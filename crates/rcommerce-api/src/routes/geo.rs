@@ -0,0 +1,94 @@
+//! Geolocation API Routes
+//!
+//! Lets storefronts read the location detected for the client and override
+//! it (e.g. from a currency or country picker):
+//! - GET    /api/v1/geo          - Detected location with currency, locale and country
+//! - PUT    /api/v1/geo/override - Override currency, locale and/or country (sets the `rc_geo` cookie)
+//! - DELETE /api/v1/geo/override - Clear the override
+//!
+//! The override changes storefront defaults only; blocked regions stay blocked.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    routing::{get, put},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+
+use crate::middleware::geoip::GEO_OVERRIDE_COOKIE;
+use crate::state::AppState;
+use rcommerce_core::models::Currency;
+use rcommerce_core::services::{GeoLocation, GeoOverride};
+use rcommerce_core::Error;
+
+/// Override cookie lifetime (30 days)
+const OVERRIDE_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Request to override the detected location
+#[derive(Debug, Deserialize)]
+pub struct OverrideRequest {
+    pub country: Option<String>,
+    pub currency: Option<Currency>,
+    pub locale: Option<String>,
+}
+
+/// GET /api/v1/geo
+pub async fn get_location(Extension(location): Extension<GeoLocation>) -> Json<GeoLocation> {
+    Json(location)
+}
+
+/// PUT /api/v1/geo/override
+pub async fn set_override(
+    State(state): State<AppState>,
+    Extension(location): Extension<GeoLocation>,
+    Json(request): Json<OverrideRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let geo_override = GeoOverride {
+        country: request.country.map(|c| c.trim().to_uppercase()),
+        currency: request.currency,
+        locale: request.locale,
+    };
+    // Round-trip through the cookie encoding so invalid values are rejected up front
+    let value = geo_override.to_cookie_value();
+    if geo_override.is_empty() || GeoOverride::from_cookie_value(&value).as_ref() != Some(&geo_override) {
+        return Err(Error::validation(
+            "Override needs a two-letter country code, a supported currency and/or a locale tag such as \"de-DE\"",
+        ));
+    }
+    if let Some(ref locale) = geo_override.locale {
+        if !state.formatting.has_locale(locale) {
+            return Err(Error::validation(format!("Unknown locale: {}", locale)));
+        }
+    }
+
+    // Reuse the middleware's detection so API lookups aren't repeated
+    let updated = state
+        .geoip
+        .locate(location.detected_country, location.source, Some(&geo_override));
+
+    let cookie = format!(
+        "{}={}; Path=/; Max-Age={}; SameSite=Lax",
+        GEO_OVERRIDE_COOKIE, value, OVERRIDE_MAX_AGE_SECS
+    );
+    Ok(([(header::SET_COOKIE, cookie)], Json(updated)))
+}
+
+/// DELETE /api/v1/geo/override
+pub async fn clear_override(
+    State(state): State<AppState>,
+    Extension(location): Extension<GeoLocation>,
+) -> impl IntoResponse {
+    let cleared = state.geoip.locate(location.detected_country, location.source, None);
+
+    let cookie = format!("{}=; Path=/; Max-Age=0; SameSite=Lax", GEO_OVERRIDE_COOKIE);
+    ([(header::SET_COOKIE, cookie)], Json(cleared))
+}
+
+/// Router for geolocation routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/geo", get(get_location))
+        .route("/geo/override", put(set_override).delete(clear_override))
+}
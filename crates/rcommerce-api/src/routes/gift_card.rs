@@ -0,0 +1,160 @@
+//! Gift Card API Routes
+//!
+//! Gift cards are issued by staff for a fixed denomination or a custom
+//! amount (`[gift_cards]`) and spent by sending their codes as `gift_cards`
+//! with `POST /checkout/complete`:
+//! - GET  /api/v1/gift-cards/options             - Denominations and custom amount range
+//! - POST /api/v1/gift-cards/balance             - Check a card's balance by code
+//! - GET  /api/v1/admin/gift-cards               - Issued cards (`?status=active`)
+//! - POST /api/v1/admin/gift-cards               - Issue a card
+//! - GET  /api/v1/admin/gift-cards/:id           - Card with its balance history
+//! - POST /api/v1/admin/gift-cards/:id/adjust    - Correct the balance
+//! - POST /api/v1/admin/gift-cards/:id/disable   - Stop the card being used
+//! - POST /api/v1/admin/gift-cards/:id/enable    - Allow a disabled card again
+//!
+//! Expired cards have their balance written off every `expiry_interval_secs`.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::jobs::spawn_singleton;
+use rcommerce_core::models::{
+    AdjustGiftCardRequest, GiftCard, GiftCardBalance, GiftCardCodeRequest, GiftCardDetails, GiftCardStatus,
+    IssueGiftCardRequest,
+};
+use rcommerce_core::Error;
+
+/// Gift cards listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing gift cards
+#[derive(Debug, Deserialize)]
+pub struct ListGiftCardsQuery {
+    pub status: Option<GiftCardStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Amounts gift cards can be issued for
+#[derive(Debug, Serialize)]
+pub struct GiftCardOptions {
+    pub currency: String,
+    pub denominations: Vec<Decimal>,
+    /// None if only the denominations can be issued
+    pub min_custom_amount: Option<Decimal>,
+    pub max_custom_amount: Option<Decimal>,
+}
+
+/// GET /api/v1/gift-cards/options
+pub async fn get_gift_card_options(State(state): State<AppState>) -> Json<GiftCardOptions> {
+    let config = state.gift_cards.config();
+    Json(GiftCardOptions {
+        currency: config.currency.clone(),
+        denominations: config.denominations.clone(),
+        min_custom_amount: config.allow_custom_amount.then_some(config.min_custom_amount),
+        max_custom_amount: config.allow_custom_amount.then_some(config.max_custom_amount),
+    })
+}
+
+/// POST /api/v1/gift-cards/balance
+pub async fn check_balance(
+    State(state): State<AppState>,
+    Json(request): Json<GiftCardCodeRequest>,
+) -> Result<Json<GiftCardBalance>, Error> {
+    Ok(Json(state.gift_cards.balance(&request.code).await?))
+}
+
+/// GET /api/v1/admin/gift-cards
+pub async fn list_gift_cards(
+    State(state): State<AppState>,
+    Query(query): Query<ListGiftCardsQuery>,
+) -> Result<Json<Vec<GiftCard>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    Ok(Json(state.gift_cards.list(query.status, limit).await?))
+}
+
+/// POST /api/v1/admin/gift-cards
+pub async fn issue_gift_card(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<IssueGiftCardRequest>,
+) -> Result<(StatusCode, Json<GiftCard>), Error> {
+    let card = state.gift_cards.issue(request, Some(auth.customer_id)).await?;
+    Ok((StatusCode::CREATED, Json(card)))
+}
+
+/// GET /api/v1/admin/gift-cards/:id
+pub async fn get_gift_card(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GiftCardDetails>, Error> {
+    Ok(Json(state.gift_cards.get_details(id).await?))
+}
+
+/// POST /api/v1/admin/gift-cards/:id/adjust
+pub async fn adjust_gift_card(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<AdjustGiftCardRequest>,
+) -> Result<Json<GiftCard>, Error> {
+    Ok(Json(state.gift_cards.adjust(id, request, auth.customer_id).await?))
+}
+
+/// POST /api/v1/admin/gift-cards/:id/disable
+pub async fn disable_gift_card(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GiftCard>, Error> {
+    Ok(Json(state.gift_cards.disable(id).await?))
+}
+
+/// POST /api/v1/admin/gift-cards/:id/enable
+pub async fn enable_gift_card(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GiftCard>, Error> {
+    Ok(Json(state.gift_cards.enable(id).await?))
+}
+
+/// Spawn the periodic write-off of expired gift cards, on the instance
+/// leading `gift_card_expiry`
+pub fn spawn_expiry(state: &AppState) {
+    let gift_cards = state.gift_cards.clone();
+    let interval = std::time::Duration::from_secs(gift_cards.config().expiry_interval_secs.max(60));
+    spawn_singleton(state.locks.clone(), "gift_card_expiry", interval, move || {
+        let gift_cards = gift_cards.clone();
+        async move {
+            match gift_cards.expire_due().await {
+                Ok(expired) if expired > 0 => tracing::info!("Expired {} gift cards", expired),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Gift card expiry failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Router for storefront gift card routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/gift-cards/options", get(get_gift_card_options))
+        .route("/gift-cards/balance", post(check_balance))
+}
+
+/// Router for gift card admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/gift-cards", get(list_gift_cards).post(issue_gift_card))
+        .route("/admin/gift-cards/:id", get(get_gift_card))
+        .route("/admin/gift-cards/:id/adjust", post(adjust_gift_card))
+        .route("/admin/gift-cards/:id/disable", post(disable_gift_card))
+        .route("/admin/gift-cards/:id/enable", post(enable_gift_card))
+}
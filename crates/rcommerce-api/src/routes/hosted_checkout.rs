@@ -0,0 +1,95 @@
+//! Hosted Checkout API Routes
+//!
+//! Gateway-hosted payment pages for an order (`[payment.hosted_checkout]`).
+//! The gateway's checkout webhooks (`/api/v1/webhooks/:gateway_id`) mark
+//! the order paid when one is completed:
+//! - POST /api/v1/orders/:id/hosted-checkout              - Checkout session for the customer's order
+//! - GET  /api/v1/admin/orders/:id/hosted-checkouts       - An order's checkouts
+//! - POST /api/v1/admin/orders/:id/hosted-checkouts       - Create a checkout session or payment link
+//! - GET  /api/v1/admin/hosted-checkouts/:id              - Get a checkout
+//! - POST /api/v1/admin/hosted-checkouts/:id/expire       - Close an open checkout
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{CreateHostedCheckoutRequest, HostedCheckout, HostedCheckoutMode};
+use rcommerce_core::Error;
+
+/// POST /api/v1/orders/:id/hosted-checkout
+pub async fn create_customer_checkout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<CreateHostedCheckoutRequest>,
+) -> Result<(StatusCode, Json<HostedCheckout>), Error> {
+    if request.mode == HostedCheckoutMode::PaymentLink && !auth.is_admin() {
+        return Err(Error::validation("Payment links are created by staff"));
+    }
+    let customer_id = (!auth.is_admin()).then_some(auth.customer_id);
+    let checkout = state
+        .hosted_checkouts
+        .create(order_id, customer_id, Some(auth.customer_id), request)
+        .await?;
+    Ok((StatusCode::CREATED, Json(checkout)))
+}
+
+/// GET /api/v1/admin/orders/:id/hosted-checkouts
+pub async fn list_order_checkouts(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<Vec<HostedCheckout>>, Error> {
+    Ok(Json(state.hosted_checkouts.list_for_order(order_id).await?))
+}
+
+/// POST /api/v1/admin/orders/:id/hosted-checkouts
+pub async fn create_checkout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<CreateHostedCheckoutRequest>,
+) -> Result<(StatusCode, Json<HostedCheckout>), Error> {
+    let checkout = state
+        .hosted_checkouts
+        .create(order_id, None, Some(auth.customer_id), request)
+        .await?;
+    Ok((StatusCode::CREATED, Json(checkout)))
+}
+
+/// GET /api/v1/admin/hosted-checkouts/:id
+pub async fn get_checkout(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HostedCheckout>, Error> {
+    Ok(Json(state.hosted_checkouts.get(id).await?))
+}
+
+/// POST /api/v1/admin/hosted-checkouts/:id/expire
+pub async fn expire_checkout(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HostedCheckout>, Error> {
+    Ok(Json(state.hosted_checkouts.expire(id).await?))
+}
+
+/// Router for customer checkout sessions
+pub fn router() -> Router<AppState> {
+    Router::new().route("/orders/:id/hosted-checkout", post(create_customer_checkout))
+}
+
+/// Admin router for checkout sessions and payment links
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/orders/:id/hosted-checkouts",
+            get(list_order_checkouts).post(create_checkout),
+        )
+        .route("/admin/hosted-checkouts/:id", get(get_checkout))
+        .route("/admin/hosted-checkouts/:id/expire", post(expire_checkout))
+}
@@ -0,0 +1,309 @@
+//! In-App Notification Routes
+//!
+//! The notification bell of the admin UI and customer portal. Staff sign in
+//! as customers, so both use the same endpoints, which need read access to
+//! customers in the token's scopes and only see the caller's notifications:
+//! - GET  /api/v1/customers/me/notifications               - Notifications, newest first, with the unread count (`?unread=true`)
+//! - GET  /api/v1/customers/me/notifications/unread-count  - Unread count
+//! - POST /api/v1/customers/me/notifications/:id/read      - Mark one read
+//! - POST /api/v1/customers/me/notifications/read-all      - Mark all read
+//! - GET  /api/v1/customers/me/notifications/live          - Upgrade to a WebSocket pushing new notifications and unread counts
+//! - POST /api/v1/admin/in-app-notifications               - Add a notification to someone's bell (admin)
+//!
+//! Browsers can't set headers on a WebSocket, so its token may also be passed
+//! as `?token=`. The socket sends `unread_count` when it opens and after
+//! notifications are read, `notification` for each new one, and answers
+//! `{"type":"ping"}` with `pong`.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::routes::account::account_owner;
+use crate::routes::invoice::ListQuery;
+use crate::state::AppState;
+use rcommerce_core::notification::in_app::{InAppChange, InAppClientMessage, InAppServerMessage};
+use rcommerce_core::notification::{InAppNotification, NewInAppNotification};
+use rcommerce_core::repository::{InAppNotificationRepository, PostgresInAppNotificationRepository};
+use rcommerce_core::services::{AuthService, Resource};
+use rcommerce_core::Error;
+
+fn repository(state: &AppState) -> PostgresInAppNotificationRepository {
+    PostgresInAppNotificationRepository::new(state.db.pool().clone())
+}
+
+/// Query parameters for the notification list
+#[derive(Debug, Default, Deserialize)]
+pub struct NotificationListQuery {
+    /// Only unread notifications
+    #[serde(default)]
+    pub unread: bool,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Query parameters of the upgrade request
+#[derive(Debug, Default, Deserialize)]
+pub struct LiveQuery {
+    /// Access token, when it can't be sent as a bearer header
+    pub token: Option<String>,
+}
+
+/// Notification list response
+#[derive(Debug, Serialize)]
+pub struct NotificationListResponse {
+    pub notifications: Vec<InAppNotification>,
+    pub unread_count: i64,
+}
+
+/// Unread count response
+#[derive(Debug, Serialize)]
+pub struct UnreadCountResponse {
+    pub unread_count: i64,
+}
+
+/// Mark all read response
+#[derive(Debug, Serialize)]
+pub struct MarkAllReadResponse {
+    /// Notifications that were unread
+    pub marked: u64,
+}
+
+/// GET /api/v1/customers/me/notifications
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<NotificationListQuery>,
+) -> Result<Json<NotificationListResponse>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    let (limit, offset) = ListQuery { page: query.page.unwrap_or(1), per_page: query.per_page.unwrap_or(20) }.limit_offset();
+    let repository = repository(&state);
+    let notifications = repository.list(customer_id, query.unread, limit, offset).await?;
+    let unread_count = repository.unread_count(customer_id).await?;
+    Ok(Json(NotificationListResponse { notifications, unread_count }))
+}
+
+/// GET /api/v1/customers/me/notifications/unread-count
+pub async fn unread_count(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<UnreadCountResponse>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    Ok(Json(UnreadCountResponse { unread_count: repository(&state).unread_count(customer_id).await? }))
+}
+
+/// POST /api/v1/customers/me/notifications/:id/read
+pub async fn mark_read(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InAppNotification>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    let notification = repository(&state)
+        .mark_read(customer_id, id)
+        .await?
+        .ok_or_else(|| Error::not_found("Notification not found"))?;
+    Ok(Json(notification))
+}
+
+/// POST /api/v1/customers/me/notifications/read-all
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<MarkAllReadResponse>, Error> {
+    let customer_id = account_owner(&auth, Resource::Customers)?;
+    Ok(Json(MarkAllReadResponse { marked: repository(&state).mark_all_read(customer_id).await? }))
+}
+
+/// POST /api/v1/admin/in-app-notifications
+pub async fn send_notification(
+    State(state): State<AppState>,
+    Json(request): Json<NewInAppNotification>,
+) -> Result<(StatusCode, Json<InAppNotification>), Error> {
+    let notification = repository(&state).create(&request).await?;
+    Ok((StatusCode::CREATED, Json(notification)))
+}
+
+/// Authenticate the socket like the auth middleware does; returns its owner
+async fn socket_owner(state: &AppState, headers: &HeaderMap, query: &LiveQuery) -> Result<Uuid, Error> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(AuthService::extract_bearer_token)
+        .or(query.token.as_deref())
+        .ok_or_else(|| Error::unauthorized("Missing access token"))?;
+    let claims = state.verify_access_token(token).await?;
+    let auth = JwtAuth { customer_id: claims.sub, email: claims.email, permissions: claims.permissions };
+    account_owner(&auth, Resource::Customers)
+}
+
+/// GET /api/v1/customers/me/notifications/live
+pub async fn live_notifications(
+    State(state): State<AppState>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, Error> {
+    let config = state.websocket.clone();
+    if !config.enabled {
+        return Err(Error::not_found("Live notifications are disabled"));
+    }
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|h| h.to_str().ok()) {
+        if !config.is_origin_allowed(origin) {
+            return Err(Error::HttpError(StatusCode::FORBIDDEN, "Origin not allowed".to_string()));
+        }
+    }
+
+    let customer_id = socket_owner(&state, &headers, &query).await?;
+    if state.in_app_feed.connection_count() >= config.max_connections {
+        return Err(Error::HttpError(StatusCode::SERVICE_UNAVAILABLE, "Too many live connections".to_string()));
+    }
+
+    Ok(ws
+        .max_message_size(config.max_message_size)
+        .on_upgrade(move |socket| run_socket(socket, state, customer_id)))
+}
+
+async fn send(socket: &mut WebSocket, message: &InAppServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+        Err(e) => {
+            tracing::error!("Failed to serialize in-app notification: {}", e);
+            true
+        }
+    }
+}
+
+/// What to push for a change to the socket owner's notifications
+async fn change_message(
+    repository: &PostgresInAppNotificationRepository,
+    change: InAppChange,
+) -> Result<InAppServerMessage, Error> {
+    let unread_count = repository.unread_count(change.customer_id).await?;
+    let notification = match change.notification_id {
+        Some(id) => repository.get(change.customer_id, id).await?,
+        None => None,
+    };
+    Ok(match notification {
+        Some(notification) => InAppServerMessage::Notification { notification, unread_count },
+        None => InAppServerMessage::UnreadCount { unread_count },
+    })
+}
+
+fn handle_client_message(text: &str) -> InAppServerMessage {
+    match serde_json::from_str::<InAppClientMessage>(text) {
+        Ok(InAppClientMessage::Ping) => InAppServerMessage::Pong,
+        Err(e) => InAppServerMessage::Error { message: format!("Invalid message: {}", e) },
+    }
+}
+
+/// Push the owner's notification changes until the client leaves or stops
+/// answering pings
+async fn run_socket(mut socket: WebSocket, state: AppState, customer_id: Uuid) {
+    let repository = repository(&state);
+    let mut changes = state.in_app_feed.subscribe();
+    let mut ping = tokio::time::interval(state.websocket.ping_interval());
+    let timeout = state.websocket.connection_timeout();
+    let mut last_heard = Instant::now();
+    // Start with the current unread count
+    let mut pending = Some(InAppChange { customer_id, notification_id: None });
+
+    loop {
+        if let Some(change) = pending.take() {
+            match change_message(&repository, change).await {
+                Ok(message) => {
+                    if !send(&mut socket, &message).await {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to load in-app notifications for customer {}: {}", customer_id, e),
+            }
+        }
+        tokio::select! {
+            change = changes.recv() => {
+                pending = match change {
+                    Ok(change) if change.customer_id == customer_id => Some(change),
+                    Ok(_) => None,
+                    // Skipped changes may have been the owner's; the count catches up
+                    Err(RecvError::Lagged(_)) => Some(InAppChange { customer_id, notification_id: None }),
+                    Err(RecvError::Closed) => break,
+                };
+            }
+            message = socket.recv() => {
+                last_heard = Instant::now();
+                let reply = match message {
+                    Some(Ok(Message::Text(text))) => handle_client_message(&text),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if !send(&mut socket, &reply).await {
+                    break;
+                }
+            }
+            _ = ping.tick() => {
+                if last_heard.elapsed() > timeout {
+                    tracing::debug!("Closing notification connection: no answer for {:?}", timeout);
+                    break;
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Forward the in-app notification trigger's changes to the sockets for the
+/// server's lifetime
+pub fn spawn_listener(state: &AppState) {
+    if !state.websocket.enabled {
+        return;
+    }
+    let feed = state.in_app_feed.clone();
+    let pool = state.db.pool().clone();
+    tokio::spawn(async move { feed.listen(pool).await });
+}
+
+/// Router for the signed-in user's notifications
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/customers/me/notifications", get(list_notifications))
+        .route("/customers/me/notifications/unread-count", get(unread_count))
+        .route("/customers/me/notifications/read-all", post(mark_all_read))
+        .route("/customers/me/notifications/:id/read", post(mark_read))
+}
+
+/// Router for the notification socket (authenticates itself)
+pub fn live_router() -> Router<AppState> {
+    Router::new().route("/customers/me/notifications/live", get(live_notifications))
+}
+
+/// Router for sending in-app notifications (admin)
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route("/admin/in-app-notifications", post(send_notification))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_messages() {
+        assert_eq!(handle_client_message(r#"{"type":"ping"}"#), InAppServerMessage::Pong);
+        assert!(matches!(handle_client_message(r#"{"type":"subscribe"}"#), InAppServerMessage::Error { .. }));
+        assert!(matches!(handle_client_message("not json"), InAppServerMessage::Error { .. }));
+    }
+}
@@ -0,0 +1,79 @@
+//! Incident API Routes
+//!
+//! Incidents are opened by the `anomaly_detection` job when the payment
+//! failure, refund or 5xx rate spikes (`[observability.anomalies]`), and
+//! resolve by themselves once the rate is back to normal:
+//! - GET  /api/v1/admin/incidents                  - Incidents, newest first (`?status=open`)
+//! - GET  /api/v1/admin/incidents/:id              - Get an incident
+//! - POST /api/v1/admin/incidents/:id/acknowledge  - Acknowledge an open incident
+//! - POST /api/v1/admin/incidents/:id/resolve      - Resolve an incident
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{Incident, IncidentStatus};
+use rcommerce_core::Error;
+
+/// Incidents listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters for listing incidents
+#[derive(Debug, Deserialize)]
+pub struct ListIncidentsQuery {
+    pub status: Option<IncidentStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/incidents
+pub async fn list_incidents(
+    State(state): State<AppState>,
+    Query(query): Query<ListIncidentsQuery>,
+) -> Result<Json<Vec<Incident>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let incidents = state.metrics.list_incidents(query.status, limit, offset).await?;
+    Ok(Json(incidents))
+}
+
+/// GET /api/v1/admin/incidents/:id
+pub async fn get_incident(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Incident>, Error> {
+    Ok(Json(state.metrics.get_incident(id).await?))
+}
+
+/// POST /api/v1/admin/incidents/:id/acknowledge
+pub async fn acknowledge_incident(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Incident>, Error> {
+    Ok(Json(state.metrics.acknowledge_incident(id, auth.customer_id).await?))
+}
+
+/// POST /api/v1/admin/incidents/:id/resolve
+pub async fn resolve_incident(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Incident>, Error> {
+    Ok(Json(state.metrics.resolve_incident(id, auth.customer_id).await?))
+}
+
+/// Admin router for incidents
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/incidents", get(list_incidents))
+        .route("/admin/incidents/:id", get(get_incident))
+        .route("/admin/incidents/:id/acknowledge", post(acknowledge_incident))
+        .route("/admin/incidents/:id/resolve", post(resolve_incident))
+}
@@ -0,0 +1,172 @@
+//! Customer Invoice API Routes
+//!
+//! Billing documents for the authenticated customer's own orders, so
+//! storefront account areas don't need admin-scoped keys. Invoices need
+//! `orders:read` in the token's scopes, receipts `payments:read`:
+//! - GET /api/v1/customers/me/invoices               - List invoices
+//! - GET /api/v1/customers/me/invoices/:order_id     - Invoice detail with payment receipts
+//! - GET /api/v1/customers/me/invoices/:order_id/pdf - Download the invoice as PDF (`?locale=` or Accept-Language)
+//! - GET /api/v1/customers/me/receipts               - Gateway receipt URLs for all payments
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::routes::account::account_owner;
+use crate::routes::formatting::accept_language;
+use crate::state::AppState;
+use rcommerce_core::models::{Invoice, InvoiceSummary, PaymentReceipt};
+use rcommerce_core::repository::{InvoiceRepository, PostgresInvoiceRepository};
+use rcommerce_core::services::{InvoiceService, Resource};
+use rcommerce_core::Error;
+
+/// Pagination query parameters
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+impl ListQuery {
+    pub(crate) fn limit_offset(&self) -> (i64, i64) {
+        let per_page = self.per_page.clamp(1, 100);
+        (per_page, (self.page.max(1) - 1) * per_page)
+    }
+}
+
+/// Query parameters for the PDF download
+#[derive(Debug, Deserialize)]
+pub struct PdfQuery {
+    pub locale: Option<String>,
+}
+
+/// Invoice list entry
+#[derive(Debug, Serialize)]
+pub struct InvoiceListItem {
+    pub invoice_number: String,
+    #[serde(flatten)]
+    pub summary: InvoiceSummary,
+    pub pdf_url: String,
+}
+
+/// Invoice detail response
+#[derive(Debug, Serialize)]
+pub struct InvoiceResponse {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub pdf_url: String,
+}
+
+pub(crate) fn pdf_url(order_id: Uuid) -> String {
+    format!("/api/v1/customers/me/invoices/{}/pdf", order_id)
+}
+
+fn repository(state: &AppState) -> PostgresInvoiceRepository {
+    PostgresInvoiceRepository::new(state.db.pool().clone())
+}
+
+async fn find_invoice(state: &AppState, auth: &JwtAuth, order_id: Uuid) -> Result<Invoice, Error> {
+    let customer_id = account_owner(auth, Resource::Orders)?;
+    repository(state)
+        .find_for_customer(customer_id, order_id)
+        .await?
+        .ok_or_else(|| Error::not_found("Invoice not found"))
+}
+
+/// GET /api/v1/customers/me/invoices
+pub async fn list_invoices(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<InvoiceListItem>>, Error> {
+    let customer_id = account_owner(&auth, Resource::Orders)?;
+    let (limit, offset) = query.limit_offset();
+    let invoices = repository(&state)
+        .list_for_customer(customer_id, limit, offset)
+        .await?
+        .into_iter()
+        .map(|summary| InvoiceListItem {
+            invoice_number: summary.invoice_number(),
+            pdf_url: pdf_url(summary.order_id),
+            summary,
+        })
+        .collect();
+
+    Ok(Json(invoices))
+}
+
+/// GET /api/v1/customers/me/invoices/:order_id
+pub async fn get_invoice(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<InvoiceResponse>, Error> {
+    let invoice = find_invoice(&state, &auth, order_id).await?;
+
+    Ok(Json(InvoiceResponse {
+        invoice,
+        pdf_url: pdf_url(order_id),
+    }))
+}
+
+/// GET /api/v1/customers/me/invoices/:order_id/pdf
+pub async fn download_invoice_pdf(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(order_id): Path<Uuid>,
+    headers: HeaderMap,
+    Query(query): Query<PdfQuery>,
+) -> Result<impl IntoResponse, Error> {
+    let invoice = find_invoice(&state, &auth, order_id).await?;
+    let locale = query.locale.or_else(|| accept_language(&headers));
+    let disposition = format!("attachment; filename=\"{}\"", InvoiceService::filename(&invoice));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        InvoiceService::render_pdf(&invoice, &state.formatting, locale.as_deref()),
+    ))
+}
+
+/// GET /api/v1/customers/me/receipts
+pub async fn list_receipts(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<PaymentReceipt>>, Error> {
+    let customer_id = account_owner(&auth, Resource::Payments)?;
+    let (limit, offset) = query.limit_offset();
+    let receipts = repository(&state)
+        .receipts_for_customer(customer_id, limit, offset)
+        .await?;
+
+    Ok(Json(receipts))
+}
+
+/// Router for customer invoice routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/customers/me/invoices", get(list_invoices))
+        .route("/customers/me/invoices/:order_id", get(get_invoice))
+        .route("/customers/me/invoices/:order_id/pdf", get(download_invoice_pdf))
+        .route("/customers/me/receipts", get(list_receipts))
+}
@@ -0,0 +1,109 @@
+//! Failed Job API Routes
+//!
+//! The dead-letter queue of recurring jobs that failed every attempt of
+//! their retry policy (`[scheduler.retry]`), with each attempt's error:
+//! - GET  /api/v1/admin/jobs/failed              - Dead letters, newest first (`?status=dead|retried|discarded|all`, default `dead`)
+//! - GET  /api/v1/admin/jobs/failed/:id          - A dead letter with its attempts
+//! - POST /api/v1/admin/jobs/failed/:id/retry    - Run the job again on the next scheduler poll
+//! - POST /api/v1/admin/jobs/failed/:id/discard  - Drop the dead letter
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::routes::invoice::ListQuery;
+use crate::state::AppState;
+use rcommerce_core::jobs::dead_letter;
+use rcommerce_core::models::{JobDeadLetter, DEAD_LETTER_DEAD, DEAD_LETTER_DISCARDED, DEAD_LETTER_RETRIED};
+use rcommerce_core::repository::{PostgresScheduledJobRepository, ScheduledJobRepository};
+use rcommerce_core::Error;
+
+fn repository(state: &AppState) -> PostgresScheduledJobRepository {
+    PostgresScheduledJobRepository::new(state.db.pool().clone())
+}
+
+/// Query parameters of the failed job list
+#[derive(Debug, Default, Deserialize)]
+pub struct FailedJobQuery {
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// The status filter of a list request; None lists every status
+fn status_filter(status: Option<&str>) -> Result<Option<&str>, Error> {
+    match status.unwrap_or(DEAD_LETTER_DEAD) {
+        "all" => Ok(None),
+        status @ (DEAD_LETTER_DEAD | DEAD_LETTER_RETRIED | DEAD_LETTER_DISCARDED) => Ok(Some(status)),
+        other => Err(Error::validation(format!(
+            "Unknown status '{}': expected dead, retried, discarded or all",
+            other
+        ))),
+    }
+}
+
+/// GET /api/v1/admin/jobs/failed
+pub async fn list_failed_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<FailedJobQuery>,
+) -> Result<Json<Vec<JobDeadLetter>>, Error> {
+    let status = status_filter(query.status.as_deref())?;
+    let (limit, offset) = ListQuery { page: query.page.unwrap_or(1), per_page: query.per_page.unwrap_or(20) }.limit_offset();
+    Ok(Json(repository(&state).dead_letters(status, limit, offset).await?))
+}
+
+/// GET /api/v1/admin/jobs/failed/:id
+pub async fn get_failed_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobDeadLetter>, Error> {
+    let entry = repository(&state)
+        .find_dead_letter(id)
+        .await?
+        .ok_or_else(|| Error::not_found("Failed job not found"))?;
+    Ok(Json(entry))
+}
+
+/// POST /api/v1/admin/jobs/failed/:id/retry
+pub async fn retry_failed_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobDeadLetter>, Error> {
+    let entry = dead_letter::retry(&repository(&state), id).await?;
+    tracing::info!("Retrying dead-lettered job {} ({})", entry.job_name, entry.id);
+    Ok(Json(entry))
+}
+
+/// POST /api/v1/admin/jobs/failed/:id/discard
+pub async fn discard_failed_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobDeadLetter>, Error> {
+    Ok(Json(dead_letter::discard(&repository(&state), id).await?))
+}
+
+/// Router for failed jobs (admin)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/jobs/failed", get(list_failed_jobs))
+        .route("/admin/jobs/failed/:id", get(get_failed_job))
+        .route("/admin/jobs/failed/:id/retry", post(retry_failed_job))
+        .route("/admin/jobs/failed/:id/discard", post(discard_failed_job))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_filter() {
+        assert_eq!(status_filter(None).unwrap(), Some("dead"));
+        assert_eq!(status_filter(Some("retried")).unwrap(), Some("retried"));
+        assert_eq!(status_filter(Some("all")).unwrap(), None);
+        assert!(status_filter(Some("failed")).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Live Event Routes
+//!
+//! Admin dashboards open a WebSocket instead of polling:
+//! - GET /api/v1/admin/live?topics=order.created,payment.updated - Upgrade to a live event socket
+//!
+//! Browsers can't set headers on a WebSocket, so the staff token may also be
+//! passed as `?token=`. Staff only get the topics whose admin routes their
+//! role may read: order.created (orders), payment.updated (payments),
+//! inventory.low_stock and stock.availability_changed (inventory) and
+//! shipment.status_changed (shipments).
+//! Once connected the client sends `{"type":"subscribe","topics":[...]}`,
+//! `{"type":"unsubscribe","topics":[...]}` or `{"type":"ping"}`, and
+//! receives `subscribed`, `event`, `error` and `pong` messages.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, Request, State,
+    },
+    http::{header, HeaderMap, Method, StatusCode},
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::middleware::{access_control, scopes};
+use crate::state::AppState;
+use rcommerce_core::services::AuthService;
+use rcommerce_core::websocket::{LiveClientMessage, LiveEvent, LiveServerMessage, LiveTopic};
+use rcommerce_core::Error;
+
+/// Query parameters of the upgrade request
+#[derive(Debug, Default, Deserialize)]
+pub struct LiveQuery {
+    /// Staff access token, when it can't be sent as a bearer header
+    pub token: Option<String>,
+    /// Comma-separated topics to subscribe to at once
+    pub topics: Option<String>,
+}
+
+/// Parse `?topics=`, keeping only the allowed ones
+fn initial_topics(topics: Option<&str>, allowed: &[LiveTopic]) -> Result<HashSet<LiveTopic>, Error> {
+    let mut subscribed = HashSet::new();
+    for topic in topics.unwrap_or("").split(',').filter(|t| !t.trim().is_empty()) {
+        let topic: LiveTopic = topic.parse()?;
+        if !allowed.contains(&topic) {
+            return Err(Error::HttpError(StatusCode::FORBIDDEN, format!("Not allowed to subscribe to {}", topic)));
+        }
+        subscribed.insert(topic);
+    }
+    Ok(subscribed)
+}
+
+/// Authenticate staff like the admin middleware does and return the topics
+/// their permissions allow
+async fn allowed_topics(state: &AppState, headers: &HeaderMap, query: &LiveQuery) -> Result<(Uuid, Vec<LiveTopic>), Error> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(AuthService::extract_bearer_token)
+        .or(query.token.as_deref())
+        .ok_or_else(|| Error::unauthorized("Missing access token"))?;
+    let claims = state.verify_access_token(token).await?;
+
+    let permissions = state
+        .roles
+        .effective_permissions(claims.sub)
+        .await?
+        .ok_or_else(|| Error::unauthorized("Unknown staff member"))?
+        .permissions;
+
+    if state.two_factor.config().required_for_admins
+        && !state.two_factor.admin_access_allowed(claims.sub, claims.iat).await?
+    {
+        return Err(Error::HttpError(
+            StatusCode::FORBIDDEN,
+            "Two-factor authentication is required for admin access; enable it at /auth/2fa/enroll and log in again".to_string(),
+        ));
+    }
+
+    let topics: Vec<LiveTopic> = LiveTopic::ALL
+        .into_iter()
+        .filter(|topic| scopes::allows_admin_route(&permissions, &Method::GET, topic.admin_route()))
+        .collect();
+    if topics.is_empty() {
+        tracing::warn!("Permission denied: customer {} tried to open live events", claims.sub);
+        return Err(Error::HttpError(StatusCode::FORBIDDEN, "No live event topics allowed".to_string()));
+    }
+    Ok((claims.sub, topics))
+}
+
+/// GET /api/v1/admin/live
+pub async fn live_events(
+    State(state): State<AppState>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+    request: Request,
+) -> Result<Response, Error> {
+    let config = state.websocket.clone();
+    if !config.enabled {
+        return Err(Error::not_found("Live events are disabled"));
+    }
+    // The socket is an admin route, so it needs the staff certificate too
+    if state.admin_requires_client_cert {
+        if let Some(denial) = access_control::client_certificate_denial(&request) {
+            access_control::record_denial(&state.access_denials, denial);
+            return Err(Error::HttpError(StatusCode::FORBIDDEN, "Client certificate required".to_string()));
+        }
+    }
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|h| h.to_str().ok()) {
+        if !config.is_origin_allowed(origin) {
+            return Err(Error::HttpError(StatusCode::FORBIDDEN, "Origin not allowed".to_string()));
+        }
+    }
+
+    let (staff_id, allowed) = allowed_topics(&state, &headers, &query).await?;
+    let subscribed = initial_topics(query.topics.as_deref(), &allowed)?;
+    if state.live_events.connection_count() >= config.max_connections {
+        return Err(Error::HttpError(StatusCode::SERVICE_UNAVAILABLE, "Too many live connections".to_string()));
+    }
+
+    tracing::info!("Live events opened by customer {}", staff_id);
+    Ok(ws
+        .max_message_size(config.max_message_size)
+        .on_upgrade(move |socket| run_socket(socket, state, allowed, subscribed)))
+}
+
+async fn send(socket: &mut WebSocket, message: &LiveServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+        Err(e) => {
+            tracing::error!("Failed to serialize live event: {}", e);
+            true
+        }
+    }
+}
+
+fn subscribed_message(subscribed: &HashSet<LiveTopic>) -> LiveServerMessage {
+    let mut topics: Vec<LiveTopic> = subscribed.iter().copied().collect();
+    topics.sort_by_key(|topic| topic.as_str());
+    LiveServerMessage::Subscribed { topics }
+}
+
+/// Apply a client message; returns the reply
+fn handle_client_message(
+    text: &str,
+    allowed: &[LiveTopic],
+    subscribed: &mut HashSet<LiveTopic>,
+) -> LiveServerMessage {
+    let message = match serde_json::from_str::<LiveClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => return LiveServerMessage::Error { message: format!("Invalid message: {}", e) },
+    };
+    match message {
+        LiveClientMessage::Subscribe { topics } => {
+            if let Some(topic) = topics.iter().find(|topic| !allowed.contains(topic)) {
+                return LiveServerMessage::Error { message: format!("Not allowed to subscribe to {}", topic) };
+            }
+            subscribed.extend(topics);
+            subscribed_message(subscribed)
+        }
+        LiveClientMessage::Unsubscribe { topics } => {
+            for topic in &topics {
+                subscribed.remove(topic);
+            }
+            subscribed_message(subscribed)
+        }
+        LiveClientMessage::Ping => LiveServerMessage::Pong,
+    }
+}
+
+/// Forward subscribed events until the client leaves or stops answering pings
+async fn run_socket(
+    mut socket: WebSocket,
+    state: AppState,
+    allowed: Vec<LiveTopic>,
+    mut subscribed: HashSet<LiveTopic>,
+) {
+    let mut events = state.live_events.subscribe();
+    let mut ping = tokio::time::interval(state.websocket.ping_interval());
+    let timeout = state.websocket.connection_timeout();
+    let mut last_heard = Instant::now();
+
+    if !subscribed.is_empty() && !send(&mut socket, &subscribed_message(&subscribed)).await {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event: LiveEvent = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        let message = format!("Connection too slow, {} events skipped", skipped);
+                        if !send(&mut socket, &LiveServerMessage::Error { message }).await {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if subscribed.contains(&event.topic) && !send(&mut socket, &LiveServerMessage::Event(event)).await {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                last_heard = Instant::now();
+                let reply = match message {
+                    Some(Ok(Message::Text(text))) => handle_client_message(&text, &allowed, &mut subscribed),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if !send(&mut socket, &reply).await {
+                    break;
+                }
+            }
+            _ = ping.tick() => {
+                if last_heard.elapsed() > timeout {
+                    tracing::debug!("Closing live events connection: no answer for {:?}", timeout);
+                    break;
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Forward bus events and the stock triggers' database events to the
+/// sockets for the server's lifetime
+pub fn spawn_listener(state: &AppState) {
+    if !state.websocket.enabled {
+        return;
+    }
+    state.events.subscribe(Arc::new(state.live_events.clone()));
+    let events = state.events.clone();
+    let pool = state.db.pool().clone();
+    tokio::spawn(async move { events.listen(pool).await });
+}
+
+/// Router for the admin live events socket (authenticates itself)
+pub fn router() -> Router<AppState> {
+    Router::new().route("/admin/live", get(live_events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriptions_are_limited_to_allowed_topics() {
+        let allowed = vec![LiveTopic::OrderCreated, LiveTopic::ShipmentStatusChanged];
+        assert_eq!(initial_topics(Some("order.created, shipment.status_changed"), &allowed).unwrap().len(), 2);
+        assert!(initial_topics(None, &allowed).unwrap().is_empty());
+        assert!(initial_topics(Some("payment.updated"), &allowed).is_err());
+        assert!(initial_topics(Some("order.deleted"), &allowed).is_err());
+
+        let mut subscribed = HashSet::new();
+        let reply = handle_client_message(r#"{"type":"subscribe","topics":["order.created"]}"#, &allowed, &mut subscribed);
+        assert_eq!(reply, LiveServerMessage::Subscribed { topics: vec![LiveTopic::OrderCreated] });
+        let reply = handle_client_message(r#"{"type":"subscribe","topics":["inventory.low_stock"]}"#, &allowed, &mut subscribed);
+        assert!(matches!(reply, LiveServerMessage::Error { .. }));
+        let reply = handle_client_message(r#"{"type":"unsubscribe","topics":["order.created"]}"#, &allowed, &mut subscribed);
+        assert_eq!(reply, LiveServerMessage::Subscribed { topics: vec![] });
+        assert_eq!(handle_client_message(r#"{"type":"ping"}"#, &allowed, &mut subscribed), LiveServerMessage::Pong);
+    }
+}
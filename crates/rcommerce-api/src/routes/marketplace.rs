@@ -0,0 +1,162 @@
+//! Marketplace API Routes
+//!
+//! Products and variants are listed on Amazon and eBay under marketplace
+//! SKUs. Price and stock changes queue a listing; the `marketplace_sync`
+//! job pushes queued listings and imports marketplace orders
+//! (`[marketplaces]`):
+//! - GET    /api/v1/admin/marketplaces/listings           - Listings (`?channel=ebay&product_id=...`)
+//! - POST   /api/v1/admin/marketplaces/listings           - List a product or variant on a marketplace
+//! - GET    /api/v1/admin/marketplaces/listings/:id       - Get a listing with its sync status
+//! - PUT    /api/v1/admin/marketplaces/listings/:id       - Update a listing
+//! - DELETE /api/v1/admin/marketplaces/listings/:id       - Stop syncing a listing
+//! - POST   /api/v1/admin/marketplaces/listings/:id/sync  - Queue a listing for the next sync
+//! - GET    /api/v1/admin/marketplaces/orders             - Imported marketplace orders
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreateMarketplaceListingRequest, MarketplaceChannel, MarketplaceListing, MarketplaceOrder,
+    UpdateMarketplaceListingRequest,
+};
+use rcommerce_core::repository::MarketplaceRepository;
+use rcommerce_core::Error;
+
+/// Orders listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing listings
+#[derive(Debug, Deserialize)]
+pub struct ListListingsQuery {
+    pub channel: Option<MarketplaceChannel>,
+    pub product_id: Option<Uuid>,
+}
+
+/// Query parameters for listing imported orders
+#[derive(Debug, Deserialize)]
+pub struct ListOrdersQuery {
+    pub channel: Option<MarketplaceChannel>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// The marketplace must be configured for its listings to sync
+fn ensure_configured(state: &AppState, channel: MarketplaceChannel) -> Result<(), Error> {
+    let configured = match channel {
+        MarketplaceChannel::Amazon => state.marketplaces.amazon.is_some(),
+        MarketplaceChannel::Ebay => state.marketplaces.ebay.is_some(),
+    };
+    if !configured {
+        return Err(Error::validation(format!(
+            "Marketplace {} is not configured under [marketplaces]",
+            channel
+        )));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/admin/marketplaces/listings
+pub async fn list_listings(
+    State(state): State<AppState>,
+    Query(query): Query<ListListingsQuery>,
+) -> Result<Json<Vec<MarketplaceListing>>, Error> {
+    let listings = state.marketplace.list_listings(query.channel, query.product_id).await?;
+    Ok(Json(listings))
+}
+
+/// POST /api/v1/admin/marketplaces/listings
+pub async fn create_listing(
+    State(state): State<AppState>,
+    Json(request): Json<CreateMarketplaceListingRequest>,
+) -> Result<(StatusCode, Json<MarketplaceListing>), Error> {
+    request.validate().map_err(|e| Error::validation(e.to_string()))?;
+    ensure_configured(&state, request.channel)?;
+    let listing = state.marketplace.create_listing(&request).await?;
+    Ok((StatusCode::CREATED, Json(listing)))
+}
+
+/// GET /api/v1/admin/marketplaces/listings/:id
+pub async fn get_listing(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MarketplaceListing>, Error> {
+    let listing = state
+        .marketplace
+        .find_listing(id)
+        .await?
+        .ok_or_else(|| Error::not_found("Marketplace listing not found"))?;
+    Ok(Json(listing))
+}
+
+/// PUT /api/v1/admin/marketplaces/listings/:id
+pub async fn update_listing(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateMarketplaceListingRequest>,
+) -> Result<Json<MarketplaceListing>, Error> {
+    request.validate().map_err(|e| Error::validation(e.to_string()))?;
+    let listing = state
+        .marketplace
+        .update_listing(id, &request)
+        .await?
+        .ok_or_else(|| Error::not_found("Marketplace listing not found"))?;
+    Ok(Json(listing))
+}
+
+/// DELETE /api/v1/admin/marketplaces/listings/:id
+///
+/// Only stops the sync; the listing stays up on the marketplace.
+pub async fn delete_listing(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    if !state.marketplace.delete_listing(id).await? {
+        return Err(Error::not_found("Marketplace listing not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/marketplaces/listings/:id/sync
+pub async fn queue_listing(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MarketplaceListing>, Error> {
+    let listing = state
+        .marketplace
+        .queue_listing(id)
+        .await?
+        .ok_or_else(|| Error::not_found("Marketplace listing not found"))?;
+    ensure_configured(&state, listing.channel)?;
+    Ok(Json(listing))
+}
+
+/// GET /api/v1/admin/marketplaces/orders
+pub async fn list_orders(
+    State(state): State<AppState>,
+    Query(query): Query<ListOrdersQuery>,
+) -> Result<Json<Vec<MarketplaceOrder>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let orders = state.marketplace.list_orders(query.channel, limit, offset).await?;
+    Ok(Json(orders))
+}
+
+/// Admin router for marketplace listings and orders
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/marketplaces/listings", get(list_listings).post(create_listing))
+        .route(
+            "/admin/marketplaces/listings/:id",
+            get(get_listing).put(update_listing).delete(delete_listing),
+        )
+        .route("/admin/marketplaces/listings/:id/sync", post(queue_listing))
+        .route("/admin/marketplaces/orders", get(list_orders))
+}
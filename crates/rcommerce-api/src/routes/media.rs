@@ -0,0 +1,276 @@
+//! Product Image API Routes
+//!
+//! Images are stored in the configured media storage with a resized copy
+//! per configured image size. Uploads are `multipart/form-data` with the
+//! image in a `file` field and optional `alt_text` and `variant_id` fields:
+//! - GET    /api/v1/admin/products/:id/images                 - A product's images in order
+//! - POST   /api/v1/admin/products/:id/images                 - Upload an image
+//! - POST   /api/v1/admin/products/:id/images/from-url        - Download an image from a URL
+//! - PUT    /api/v1/admin/products/:id/images/order           - Put the images in order
+//! - PUT    /api/v1/admin/products/:id/images/:image_id       - Update alt text or variant
+//! - DELETE /api/v1/admin/products/:id/images/:image_id       - Delete an image
+//! - GET    /uploads/*key                                     - Stored files (local storage)
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{DefaultBodyLimit, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::media::ImageFormat;
+use rcommerce_core::models::{
+    AddProductImageRequest, ProductImage, ReorderProductImagesRequest, UpdateProductImageRequest,
+};
+use rcommerce_core::Error;
+
+/// Room for the multipart boundaries and form fields around the image
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// GET /api/v1/admin/products/:id/images
+pub async fn list_images(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<Vec<ProductImage>>, Error> {
+    Ok(Json(state.media.list_images(product_id).await?))
+}
+
+/// POST /api/v1/admin/products/:id/images
+pub async fn upload_image(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<(StatusCode, Json<ProductImage>), Error> {
+    let boundary = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(multipart_boundary)
+        .ok_or_else(|| Error::validation("Uploads must be multipart/form-data"))?;
+    let limit = state.media.max_upload_bytes() + MULTIPART_OVERHEAD_BYTES;
+    let body = to_bytes(body, limit).await.map_err(|_| {
+        Error::validation(format!("Images can be at most {} bytes", state.media.max_upload_bytes()))
+    })?;
+
+    let mut file = None;
+    let mut alt_text = None;
+    let mut variant_id = None;
+    for field in parse_multipart(&body, &boundary)? {
+        match field.name.as_str() {
+            "file" => file = Some(field.data),
+            "alt_text" => alt_text = Some(field.text()?).filter(|text| !text.is_empty()),
+            "variant_id" => {
+                let text = field.text()?;
+                if !text.is_empty() {
+                    variant_id = Some(Uuid::parse_str(&text).map_err(|_| Error::validation("Invalid variant_id"))?);
+                }
+            }
+            _ => {}
+        }
+    }
+    let file = file.ok_or_else(|| Error::validation("No file field in the upload"))?;
+
+    let image = state.media.upload_image(product_id, file, alt_text, variant_id).await?;
+    Ok((StatusCode::CREATED, Json(image)))
+}
+
+/// POST /api/v1/admin/products/:id/images/from-url
+pub async fn add_image_from_url(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<AddProductImageRequest>,
+) -> Result<(StatusCode, Json<ProductImage>), Error> {
+    let image = state.media.import_image(product_id, request).await?;
+    Ok((StatusCode::CREATED, Json(image)))
+}
+
+/// PUT /api/v1/admin/products/:id/images/order
+pub async fn reorder_images(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<ReorderProductImagesRequest>,
+) -> Result<Json<Vec<ProductImage>>, Error> {
+    Ok(Json(state.media.reorder_images(product_id, request).await?))
+}
+
+/// PUT /api/v1/admin/products/:id/images/:image_id
+pub async fn update_image(
+    State(state): State<AppState>,
+    Path((product_id, image_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateProductImageRequest>,
+) -> Result<Json<ProductImage>, Error> {
+    Ok(Json(state.media.update_image(product_id, image_id, request).await?))
+}
+
+/// DELETE /api/v1/admin/products/:id/images/:image_id
+pub async fn delete_image(
+    State(state): State<AppState>,
+    Path((product_id, image_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.media.delete_image(product_id, image_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /uploads/*key
+pub async fn serve_upload(State(state): State<AppState>, Path(key): Path<String>) -> Result<Response, Error> {
+    let content_type = key
+        .rsplit_once('.')
+        .and_then(|(_, extension)| ImageFormat::from_extension(extension))
+        .map(|format| format.content_type())
+        .ok_or_else(|| Error::not_found("File not found"))?;
+    let data = state.media.storage().get(&key).await.map_err(|e| match e {
+        Error::Validation(_) => Error::not_found("File not found"),
+        e => e,
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+/// A form field of a multipart body
+#[derive(Debug)]
+struct MultipartField {
+    name: String,
+    data: Vec<u8>,
+}
+
+impl MultipartField {
+    fn text(self) -> Result<String, Error> {
+        String::from_utf8(self.data)
+            .map(|text| text.trim().to_string())
+            .map_err(|_| Error::validation(format!("Field {} is not UTF-8 text", self.name)))
+    }
+}
+
+/// The boundary of a `multipart/form-data` content type
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    let mut parts = content_type.split(';');
+    if !parts.next()?.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    parts
+        .filter_map(|part| part.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
+
+/// The named fields of a `multipart/form-data` body (RFC 7578)
+fn parse_multipart(body: &[u8], boundary: &str) -> Result<Vec<MultipartField>, Error> {
+    let malformed = || Error::validation("Malformed multipart body");
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let next_delimiter = format!("\r\n--{}", boundary).into_bytes();
+
+    let mut fields = Vec::new();
+    let mut position = find(body, &delimiter, 0).ok_or_else(malformed)? + delimiter.len();
+    loop {
+        // `--` after a delimiter closes the body
+        if body.get(position..position + 2) == Some(b"--") {
+            return Ok(fields);
+        }
+        if body.get(position..position + 2) != Some(b"\r\n") {
+            return Err(malformed());
+        }
+        let headers_start = position + 2;
+        let headers_end = find(body, b"\r\n\r\n", headers_start).ok_or_else(malformed)?;
+        let data_end = find(body, &next_delimiter, headers_end + 4).ok_or_else(malformed)?;
+
+        let headers = String::from_utf8_lossy(&body[headers_start..headers_end]);
+        let name = headers
+            .split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-disposition"))
+            .and_then(|(_, value)| {
+                value
+                    .split(';')
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(key, _)| key.trim() == "name")
+                    .map(|(_, name)| name.trim().trim_matches('"').to_string())
+            });
+        if let Some(name) = name {
+            fields.push(MultipartField {
+                name,
+                data: body[headers_end + 4..data_end].to_vec(),
+            });
+        }
+        position = data_end + next_delimiter.len();
+    }
+}
+
+/// Router for product image admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/products/:id/images",
+            // Upload size is limited by `media.max_upload_bytes` instead
+            get(list_images).post(upload_image).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/admin/products/:id/images/from-url", post(add_image_from_url))
+        .route("/admin/products/:id/images/order", put(reorder_images))
+        .route(
+            "/admin/products/:id/images/:image_id",
+            put(update_image).delete(delete_image),
+        )
+}
+
+/// Router for stored files
+pub fn uploads_router() -> Router<AppState> {
+    Router::new().route("/uploads/*key", get(serve_upload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_boundary() {
+        assert_eq!(
+            multipart_boundary("multipart/form-data; boundary=----abc123").as_deref(),
+            Some("----abc123")
+        );
+        assert_eq!(multipart_boundary("multipart/form-data; boundary=\"a b\"").as_deref(), Some("a b"));
+        assert_eq!(multipart_boundary("application/json"), None);
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let body = b"--XyZ\r\n\
+            Content-Disposition: form-data; name=\"alt_text\"\r\n\r\n\
+            Red shirt\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"shirt.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            \x89PNG\r\n\x1a\n\r\n--XyZ--\r\n";
+
+        let fields = parse_multipart(body, "XyZ").unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "alt_text");
+        assert_eq!(fields[0].data, b"Red shirt");
+        assert_eq!(fields[1].name, "file");
+        assert_eq!(fields[1].data, b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_parse_multipart_rejects_truncated_body() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\ndata";
+        assert!(parse_multipart(body, "XyZ").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! Business Metrics Route
+//!
+//! - GET /metrics - Orders, payments and the checkout funnel in the Prometheus
+//!   text format (`[features] metrics`; a Bearer token when
+//!   `observability.metrics_token` is set)
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::state::AppState;
+use rcommerce_core::observability::token_matches;
+
+/// Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// GET /metrics
+pub async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if !state.metrics_endpoint {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    if let Some(ref token) = state.metrics.config().metrics_token {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !provided.is_some_and(|provided| token_matches(token, provided)) {
+            return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response();
+        }
+    }
+
+    match state.metrics.render_prometheus().await {
+        Ok(body) => ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to collect business metrics: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE.into_response()
+        }
+    }
+}
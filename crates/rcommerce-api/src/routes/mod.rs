@@ -1,35 +1,200 @@
 pub mod admin;
 pub mod auth;
+pub mod cache;
+pub mod capture;
 pub mod cart;
 pub mod checkout;
 pub mod coupon;
 pub mod customer;
+pub mod exchange_rate;
+pub mod formatting;
+pub mod geo;
+pub mod invoice;
+pub mod account;
+pub mod notification_template;
 pub mod order;
+pub mod order_archive;
+pub mod access_denial;
+pub mod access_log;
+pub mod audit;
+pub mod soft_delete;
+pub mod live;
+pub mod stock_events;
+pub mod partitions;
 pub mod payment;
+pub mod price_history;
 pub mod product;
 pub mod subscription;
+pub mod subscription_plan;
+pub mod stock_adjustment;
+pub mod register;
+pub mod price_list;
+pub mod address;
+pub mod addon;
+pub mod delivery;
+pub mod checkout_field;
+pub mod gift_card;
+pub mod flash_sale;
+pub mod export;
+pub mod purchase_limit;
+pub mod returns;
+pub mod marketplace;
+pub mod metrics;
+pub mod openapi;
+pub mod automation;
+pub mod purchasing;
+pub mod supplier_feeds;
+pub mod po_dispatch;
+pub mod event_schema;
+pub mod shipments;
+pub mod tracking;
+pub mod pickup;
+pub mod reports;
+pub mod analytics;
+pub mod sales;
+pub mod hosted_checkout;
+pub mod wallet;
+pub mod catalog_promotion;
+pub mod redirects;
+pub mod customer_group;
+pub mod attribute;
+pub mod category;
+pub mod collection;
+pub mod media;
+pub mod customs;
+pub mod printing;
+pub mod incidents;
+pub mod fraud;
+pub mod reconciliation;
+pub mod jobs;
+pub mod storefront;
+pub mod roles;
+pub mod variant;
 pub mod statistics;
 pub mod dunning;
 pub mod downloads;
+pub mod email_events;
+pub mod unsubscribe;
+pub mod in_app_notification;
 pub mod webhook;
+pub mod webhook_replay;
+pub mod two_factor;
+pub mod oauth;
 
 pub use admin::router as admin_router;
 pub use auth::public_router as auth_public_router;
+pub use cache::router as cache_router;
+pub use capture::router as capture_router;
 pub use auth::protected_router as auth_protected_router;
+pub use two_factor::router as two_factor_router;
+pub use two_factor::admin_router as two_factor_admin_router;
+pub use oauth::public_router as oauth_public_router;
+pub use oauth::router as oauth_router;
 pub use cart::public_router as cart_public_router;
 pub use cart::protected_router as cart_protected_router;
 pub use cart::router as cart_router;
 pub use checkout::router as checkout_router;
 pub use coupon::router as coupon_router;
 pub use customer::router as customer_router;
+pub use exchange_rate::router as exchange_rate_router;
+pub use formatting::router as formatting_router;
+pub use geo::router as geo_router;
+pub use invoice::router as invoice_router;
+pub use account::router as account_router;
+pub use notification_template::router as notification_template_router;
 pub use order::router as order_router;
+pub use order::admin_router as order_admin_router;
+pub use order_archive::router as order_archive_router;
+pub use access_denial::router as access_denial_router;
+pub use access_log::router as access_log_router;
+pub use audit::router as audit_router;
+pub use soft_delete::router as soft_delete_router;
+pub use live::router as live_router;
+pub use stock_events::router as stock_events_router;
+pub use email_events::router as email_events_router;
+pub use unsubscribe::router as unsubscribe_router;
+pub use in_app_notification::router as in_app_notification_router;
+pub use in_app_notification::live_router as in_app_notification_live_router;
+pub use in_app_notification::admin_router as in_app_notification_admin_router;
+pub use partitions::router as partitions_router;
 pub use payment::router as payment_router;
+pub use payment::admin_router as payment_admin_router;
+pub use payment::public_router as payment_public_router;
+pub use price_history::router as price_history_router;
 pub use product::router as product_router;
+pub use variant::router as variant_router;
+pub use variant::admin_router as variant_admin_router;
 pub use subscription::router as subscription_router;
+pub use subscription_plan::router as subscription_plan_router;
+pub use subscription_plan::admin_router as subscription_plan_admin_router;
+pub use stock_adjustment::router as stock_adjustment_router;
+pub use stock_adjustment::admin_router as stock_adjustment_admin_router;
+pub use register::router as register_router;
+pub use price_list::router as price_list_router;
+pub use price_list::admin_router as price_list_admin_router;
+pub use address::router as address_router;
+pub use addon::router as addon_router;
+pub use addon::admin_router as addon_admin_router;
+pub use delivery::router as delivery_router;
+pub use delivery::admin_router as delivery_admin_router;
+pub use checkout_field::router as checkout_field_router;
+pub use checkout_field::admin_router as checkout_field_admin_router;
+pub use gift_card::router as gift_card_router;
+pub use gift_card::admin_router as gift_card_admin_router;
+pub use flash_sale::router as flash_sale_router;
+pub use flash_sale::admin_router as flash_sale_admin_router;
+pub use export::router as export_router;
+pub use purchase_limit::admin_router as purchase_limit_admin_router;
+pub use returns::router as returns_router;
+pub use returns::admin_router as returns_admin_router;
+pub use marketplace::admin_router as marketplace_admin_router;
+pub use automation::admin_router as automation_admin_router;
+pub use purchasing::admin_router as purchasing_admin_router;
+pub use supplier_feeds::admin_router as supplier_feeds_admin_router;
+pub use supplier_feeds::public_router as supplier_feeds_public_router;
+pub use po_dispatch::admin_router as po_dispatch_admin_router;
+pub use po_dispatch::public_router as po_dispatch_public_router;
+pub use event_schema::admin_router as event_schema_admin_router;
+pub use shipments::router as shipments_router;
+pub use shipments::admin_router as shipments_admin_router;
+pub use tracking::router as tracking_router;
+pub use tracking::admin_router as tracking_admin_router;
+pub use pickup::router as pickup_router;
+pub use pickup::admin_router as pickup_admin_router;
+pub use reports::admin_router as reports_admin_router;
+pub use reports::router as report_download_router;
+pub use analytics::router as analytics_router;
+pub use analytics::admin_router as analytics_admin_router;
+pub use sales::admin_router as sales_admin_router;
+pub use hosted_checkout::router as hosted_checkout_router;
+pub use hosted_checkout::admin_router as hosted_checkout_admin_router;
+pub use wallet::router as wallet_router;
+pub use wallet::admin_router as wallet_admin_router;
+pub use catalog_promotion::admin_router as catalog_promotion_admin_router;
+pub use redirects::router as redirects_router;
+pub use redirects::admin_router as redirects_admin_router;
+pub use customer_group::admin_router as customer_group_admin_router;
+pub use attribute::admin_router as attribute_admin_router;
+pub use category::router as category_router;
+pub use category::admin_router as category_admin_router;
+pub use collection::router as collection_router;
+pub use collection::admin_router as collection_admin_router;
+pub use media::admin_router as media_admin_router;
+pub use media::uploads_router;
+pub use openapi::router as openapi_router;
+pub use customs::admin_router as customs_admin_router;
+pub use printing::admin_router as printing_admin_router;
+pub use incidents::admin_router as incident_admin_router;
+pub use fraud::admin_router as fraud_admin_router;
+pub use reconciliation::admin_router as reconciliation_admin_router;
+pub use jobs::admin_router as jobs_admin_router;
+pub use roles::admin_router as roles_admin_router;
+pub use storefront::router as storefront_router;
 pub use statistics::router as statistics_router;
 pub use dunning::router as dunning_router;
 pub use downloads::router as downloads_router;
 pub use webhook::router as webhook_router;
+pub use webhook_replay::router as webhook_replay_router;
 
 use crate::state::AppState;
 use axum::{routing::get, Router};
@@ -69,6 +234,8 @@ fn api_v1_routes() -> Router<AppState> {
         .merge(coupon_router())
         .merge(payment_router())
         .merge(subscription_router())
+        .merge(subscription_plan_router())
+        .merge(subscription_plan_admin_router())
         .merge(admin_router())
         .merge(statistics_router())
         .merge(dunning_router())
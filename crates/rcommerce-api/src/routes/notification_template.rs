@@ -0,0 +1,114 @@
+//! Notification Template API Routes
+//!
+//! Admin-only endpoints for editing DB-stored email templates:
+//! - GET /api/v1/admin/notification-templates/variables                 - Variables catalog for all template types
+//! - GET /api/v1/admin/notification-templates/variables/:template_type  - Variables for one template type
+//! - GET /api/v1/admin/notification-templates                           - Stored templates
+//! - GET /api/v1/admin/notification-templates/:name                     - Stored template by name
+//! - PUT /api/v1/admin/notification-templates/:name                     - Create or replace (placeholders validated)
+//!
+//! Active email templates named after a template type override the built-in
+//! one, and one named `layout` wraps their HTML. Saving applies them on this
+//! instance right away and on the others at their next reload.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use tracing::warn;
+
+use crate::state::AppState;
+use rcommerce_core::notification::catalog::{self, TemplateCatalogEntry};
+use rcommerce_core::notification::theme::{self, TemplateIssue};
+use rcommerce_core::repository::{
+    NotificationTemplateRecord, NotificationTemplateRepository, PostgresNotificationTemplateRepository,
+    SaveNotificationTemplateRequest,
+};
+use rcommerce_core::Error;
+
+/// How often each instance reloads the stored templates
+const THEME_RELOAD_INTERVAL: Duration = Duration::from_secs(300);
+
+fn repository(state: &AppState) -> PostgresNotificationTemplateRepository {
+    PostgresNotificationTemplateRepository::new(state.db.pool().clone())
+}
+
+/// Load the branding, template directory and stored templates and install
+/// them for the emails this process sends; returns the overrides' issues
+pub async fn reload_theme(state: &AppState) -> Result<Vec<TemplateIssue>, Error> {
+    let email_theme = theme::load(&state.email, Some(&repository(state))).await?;
+    let issues = email_theme.validate();
+    theme::install(email_theme);
+    Ok(issues)
+}
+
+/// Reload the email theme periodically, so templates saved through another
+/// instance are picked up
+pub fn spawn_theme_reload(state: &AppState) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(THEME_RELOAD_INTERVAL);
+        // The first tick completes immediately; create_app_state already loaded it
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = reload_theme(&state).await {
+                warn!("Failed to reload email templates, keeping the current ones: {}", e);
+            }
+        }
+    });
+}
+
+/// GET /api/v1/admin/notification-templates/variables
+pub async fn list_variables() -> Json<Vec<TemplateCatalogEntry>> {
+    Json(catalog::catalog())
+}
+
+/// GET /api/v1/admin/notification-templates/variables/:template_type
+pub async fn get_variables(Path(template_type): Path<String>) -> Result<Json<TemplateCatalogEntry>, Error> {
+    catalog::catalog_entry(&template_type)
+        .map(Json)
+        .ok_or_else(|| Error::not_found(format!("Unknown template type: {}", template_type)))
+}
+
+/// GET /api/v1/admin/notification-templates
+pub async fn list_templates(State(state): State<AppState>) -> Result<Json<Vec<NotificationTemplateRecord>>, Error> {
+    Ok(Json(repository(&state).list().await?))
+}
+
+/// GET /api/v1/admin/notification-templates/:name
+pub async fn get_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<NotificationTemplateRecord>, Error> {
+    repository(&state)
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| Error::not_found("Notification template not found"))
+}
+
+/// PUT /api/v1/admin/notification-templates/:name
+pub async fn save_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<SaveNotificationTemplateRequest>,
+) -> Result<Json<NotificationTemplateRecord>, Error> {
+    let template = repository(&state).save(&name, request).await?;
+    if let Err(e) = reload_theme(&state).await {
+        warn!("Saved notification template {} but failed to reload email templates: {}", name, e);
+    }
+    Ok(Json(template))
+}
+
+/// Router for notification template routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/notification-templates", get(list_templates))
+        .route("/admin/notification-templates/variables", get(list_variables))
+        .route("/admin/notification-templates/variables/:template_type", get(get_variables))
+        .route("/admin/notification-templates/:name", get(get_template).put(save_template))
+}
@@ -0,0 +1,259 @@
+//! Social Login API Routes
+//!
+//! Login with Google, Apple and GitHub (OAuth 2.0 / OpenID Connect):
+//! - GET    /api/v1/auth/oauth/providers              - Enabled providers
+//! - GET    /api/v1/auth/oauth/:provider/authorize    - Redirect to the provider
+//! - GET    /api/v1/auth/oauth/:provider/callback     - Provider redirect back (Apple POSTs it)
+//! - POST   /api/v1/auth/oauth/login                  - Exchange the login code for tokens
+//! - POST   /api/v1/auth/oauth/session                - Exchange the login code for a cookie session
+//!
+//! And for the signed-in account:
+//! - GET    /api/v1/auth/oauth/identities             - Linked provider accounts
+//! - DELETE /api/v1/auth/oauth/identities/:id         - Unlink one
+
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post},
+    Form, Json, Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::middleware::geoip::ClientIp;
+use crate::openapi::ErrorResponse;
+use crate::routes::auth::{cookie_login, login_device, session_store, token_login, two_factor_challenge};
+use crate::state::AppState;
+use rcommerce_core::models::{Customer, OAuthCallbackParams, OAuthIdentity, OAuthProvider, TwoFactorLoginKind};
+use rcommerce_core::Error;
+
+/// Enabled login providers
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct OAuthProvidersResponse {
+    pub providers: Vec<OAuthProvider>,
+}
+
+/// Where to return after the provider
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct AuthorizeQuery {
+    /// Storefront URL to return to; must start with an allowed return URL
+    pub return_to: String,
+    /// Opaque value handed back to `return_to` as `state`, to check the
+    /// login was started by this browser
+    pub state: Option<String>,
+}
+
+/// One-time login code from the `oauth_code` parameter of `return_to`
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct OAuthLoginRequest {
+    pub code: String,
+}
+
+/// Provider accounts linked to the signed-in account
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct OAuthIdentitiesResponse {
+    pub identities: Vec<OAuthIdentity>,
+}
+
+fn provider(name: &str) -> Result<OAuthProvider, Error> {
+    name.parse()
+}
+
+/// Login providers customers can choose from
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/providers",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Enabled providers", body = OAuthProvidersResponse),
+    )
+)]
+pub async fn list_providers(State(state): State<AppState>) -> Json<OAuthProvidersResponse> {
+    Json(OAuthProvidersResponse { providers: state.oauth.providers() })
+}
+
+/// Start a login with a provider
+///
+/// Redirects the browser to the provider. Afterwards it returns to
+/// `return_to` with `oauth_code` (exchange it at `/auth/oauth/login` or
+/// `/auth/oauth/session`) or `oauth_error`, plus the `state` passed here.
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/{provider}/authorize",
+    tag = "auth",
+    params(
+        ("provider" = String, Path, description = "google, apple or github"),
+        AuthorizeQuery,
+    ),
+    responses(
+        (status = 303, description = "Redirect to the provider"),
+        (status = 400, description = "return_to is not allowed", body = ErrorResponse),
+        (status = 404, description = "Provider not enabled", body = ErrorResponse),
+    )
+)]
+pub async fn authorize(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<AuthorizeQuery>,
+) -> Result<Redirect, Error> {
+    let url = state
+        .oauth
+        .authorize(provider(&name)?, &query.return_to, query.state.as_deref(), Utc::now())
+        .await?;
+    Ok(Redirect::to(&url))
+}
+
+/// GET /api/v1/auth/oauth/:provider/callback
+///
+/// Where the provider sends the browser back; redirects on to the storefront.
+pub async fn callback(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<OAuthCallbackParams>,
+) -> Result<Redirect, Error> {
+    let url = state.oauth.callback(provider(&name)?, &params, Utc::now()).await?;
+    Ok(Redirect::to(&url))
+}
+
+/// POST /api/v1/auth/oauth/:provider/callback
+///
+/// Apple's callback, posted as a form.
+pub async fn callback_form(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Form(params): Form<OAuthCallbackParams>,
+) -> Result<Redirect, Error> {
+    let url = state.oauth.callback(provider(&name)?, &params, Utc::now()).await?;
+    Ok(Redirect::to(&url))
+}
+
+async fn code_customer(state: &AppState, code: &str) -> Result<Customer, Error> {
+    let customer_id = state.oauth.exchange_login_code(code, Utc::now()).await?;
+    state
+        .customer_service
+        .find_by_id(customer_id)
+        .await?
+        .ok_or_else(|| Error::unauthorized("Customer not found"))
+}
+
+/// Finish a social login with tokens
+///
+/// Accounts with 2FA get a challenge to complete at `/auth/2fa/verify`, as
+/// with a password login.
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/login",
+    tag = "auth",
+    request_body = OAuthLoginRequest,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = crate::routes::auth::LoginResponse),
+        (status = 202, description = "Two-factor code required; complete at /auth/2fa/verify", body = crate::routes::auth::TwoFactorChallengeResponse),
+        (status = 401, description = "Invalid or expired login code", body = ErrorResponse),
+    )
+)]
+pub async fn oauth_login(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
+    Json(payload): Json<OAuthLoginRequest>,
+) -> Result<Response, Error> {
+    let customer = code_customer(&state, &payload.code).await?;
+    let device = login_device(&headers, client_ip);
+
+    if state.two_factor.is_enabled(customer.id).await? {
+        return two_factor_challenge(&state, customer.id, TwoFactorLoginKind::Token, &device).await;
+    }
+    Ok(Json(token_login(&state, customer, &device).await?).into_response())
+}
+
+/// Finish a social login with a cookie session
+#[utoipa::path(
+    post,
+    path = "/auth/oauth/session",
+    tag = "auth",
+    request_body = OAuthLoginRequest,
+    responses(
+        (status = 201, description = "Session started; the session and CSRF cookies are set", body = crate::routes::auth::SessionResponse),
+        (status = 202, description = "Two-factor code required; complete at /auth/2fa/verify", body = crate::routes::auth::TwoFactorChallengeResponse),
+        (status = 401, description = "Invalid or expired login code", body = ErrorResponse),
+    )
+)]
+pub async fn oauth_session(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
+    Json(payload): Json<OAuthLoginRequest>,
+) -> Result<Response, Error> {
+    session_store(&state)?;
+    let customer = code_customer(&state, &payload.code).await?;
+
+    if state.two_factor.is_enabled(customer.id).await? {
+        let device = login_device(&headers, client_ip);
+        return two_factor_challenge(&state, customer.id, TwoFactorLoginKind::Cookie, &device).await;
+    }
+    cookie_login(&state, customer).await
+}
+
+/// Provider accounts linked to the signed-in account
+#[utoipa::path(
+    get,
+    path = "/auth/oauth/identities",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Linked provider accounts", body = OAuthIdentitiesResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn list_identities(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<OAuthIdentitiesResponse>, Error> {
+    let identities = state.oauth.identities(auth.customer_id).await?;
+    Ok(Json(OAuthIdentitiesResponse { identities }))
+}
+
+/// Unlink a provider account
+///
+/// Refused for the only provider of an account without a password.
+#[utoipa::path(
+    delete,
+    path = "/auth/oauth/identities/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "Linked account ID")),
+    responses(
+        (status = 204, description = "Unlinked"),
+        (status = 400, description = "It is the account's only way to log in", body = ErrorResponse),
+        (status = 404, description = "No such linked account", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn unlink_identity(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.oauth.unlink(auth.customer_id, id).await?;
+    tracing::info!("Customer {} unlinked login identity {}", auth.customer_id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for logging in with a provider
+pub fn public_router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/oauth/providers", get(list_providers))
+        .route("/auth/oauth/:provider/authorize", get(authorize))
+        .route("/auth/oauth/:provider/callback", get(callback).post(callback_form))
+        .route("/auth/oauth/login", post(oauth_login))
+        .route("/auth/oauth/session", post(oauth_session))
+}
+
+/// Router for the signed-in account's linked providers
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/oauth/identities", get(list_identities))
+        .route("/auth/oauth/identities/:id", delete(unlink_identity))
+}
@@ -8,6 +8,11 @@ use rust_decimal::Decimal;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use rcommerce_core::repository::{enqueue_event, OrderArchiveRepository, PostgresOrderArchiveRepository};
+use rcommerce_core::events::{status_label, DomainEvent, OrderCreated};
+use rcommerce_core::models::{after_cursor_sql, Cursor, CursorPage};
+use rcommerce_core::order::OrderCalculator;
+use rcommerce_core::services::bundle_service::stock_lines;
 use rcommerce_core::tax::TaxService;
 
 use crate::state::AppState;
@@ -69,7 +74,7 @@ pub struct OrderItemResponse {
 /// List orders
 pub async fn list_orders(State(state): State<AppState>) -> Json<serde_json::Value> {
     match sqlx::query_as::<_, rcommerce_core::models::Order>(
-        "SELECT * FROM orders ORDER BY created_at DESC LIMIT 50",
+        "SELECT * FROM orders WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT 50",
     )
     .fetch_all(state.db.pool())
     .await
@@ -124,19 +129,14 @@ pub async fn get_order(
     Path(id): Path<Uuid>,
 ) -> Result<Json<OrderResponse>, (StatusCode, Json<serde_json::Value>)> {
     let order = match sqlx::query_as::<_, rcommerce_core::models::Order>(
-        "SELECT * FROM orders WHERE id = $1",
+        "SELECT * FROM orders WHERE id = $1 AND deleted_at IS NULL",
     )
     .bind(id)
     .fetch_optional(state.db.pool())
     .await
     {
         Ok(Some(o)) => o,
-        Ok(None) => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": format!("Order {} not found", id)})),
-            ));
-        }
+        Ok(None) => return get_archived_order(&state, id).await,
         Err(e) => {
             tracing::error!("Database error: {}", e);
             return Err((
@@ -164,6 +164,42 @@ pub async fn get_order(
         }
     };
 
+    Ok(Json(order_response(order, items)))
+}
+
+/// Read through to the order archive for orders moved out of `orders`
+async fn get_archived_order(
+    state: &AppState,
+    id: Uuid,
+) -> Result<Json<OrderResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let archive = PostgresOrderArchiveRepository::new(state.db.pool().clone());
+    let archived = match archive.find_order(id).await {
+        Ok(Some(order)) => archive.find_items(id).await.map(|items| (order, items)),
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": format!("Order {} not found", id)})),
+            ));
+        }
+        Err(e) => Err(e),
+    };
+
+    match archived {
+        Ok((order, items)) => Ok(Json(order_response(order, items))),
+        Err(e) => {
+            tracing::error!("Failed to read archived order {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            ))
+        }
+    }
+}
+
+fn order_response(
+    order: rcommerce_core::models::Order,
+    items: Vec<rcommerce_core::models::OrderItem>,
+) -> OrderResponse {
     let item_responses: Vec<OrderItemResponse> = items
         .into_iter()
         .map(|i| OrderItemResponse {
@@ -178,7 +214,7 @@ pub async fn get_order(
         })
         .collect();
 
-    Ok(Json(OrderResponse {
+    OrderResponse {
         id: order.id,
         order_number: order.order_number,
         customer_id: order.customer_id,
@@ -193,7 +229,7 @@ pub async fn get_order(
         total: order.total,
         items: item_responses,
         created_at: order.created_at.to_rfc3339(),
-    }))
+    }
 }
 
 /// Create a new order
@@ -202,7 +238,7 @@ pub async fn get_order(
 /// For a full checkout flow with shipping selection, use the /checkout endpoints.
 pub async fn create_order(
     State(state): State<AppState>,
-    Extension(_auth): Extension<JwtAuth>,
+    Extension(auth): Extension<JwtAuth>,
     Json(request): Json<CreateOrderRequest>,
 ) -> Result<(StatusCode, Json<OrderResponse>), (StatusCode, Json<serde_json::Value>)> {
     // Validate email
@@ -221,6 +257,30 @@ pub async fn create_order(
         ));
     }
 
+    // Only staff may order (and get group prices) for another customer
+    let customer_id = request.customer_id.unwrap_or(auth.customer_id);
+    if customer_id != auth.customer_id && !auth.is_admin() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Cannot create orders for another customer"})),
+        ));
+    }
+
+    // Members of a customer group pay its prices
+    let mut calculator = OrderCalculator::new(Decimal::ZERO, Decimal::ZERO);
+    let product_ids: Vec<Uuid> = request.items.iter().map(|i| i.product_id).collect();
+    match state.customer_groups.pricing_for_customer(customer_id, &product_ids).await {
+        Ok(Some(pricing)) => calculator = calculator.with_customer_group(pricing),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to load customer group pricing: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Database error"})),
+            ));
+        }
+    }
+
     let mut order_items = Vec::new();
     let mut subtotal = Decimal::ZERO;
     let mut taxable_items = Vec::new();
@@ -236,7 +296,7 @@ pub async fn create_order(
 
         // Get product details
         let product = match sqlx::query_as::<_, rcommerce_core::models::Product>(
-            "SELECT * FROM products WHERE id = $1 AND is_active = true",
+            "SELECT * FROM products WHERE id = $1 AND is_active = true AND deleted_at IS NULL",
         )
         .bind(item.product_id)
         .fetch_optional(state.db.pool())
@@ -260,36 +320,8 @@ pub async fn create_order(
             }
         };
 
-        // Check inventory
-        let inventory_qty: i32 =
-            match sqlx::query_scalar("SELECT inventory_quantity FROM products WHERE id = $1")
-                .bind(item.product_id)
-                .fetch_one(state.db.pool())
-                .await
-            {
-                Ok(qty) => qty,
-                Err(e) => {
-                    tracing::error!("Database error: {}", e);
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": "Database error"})),
-                    ));
-                }
-            };
-
-        if inventory_qty < item.quantity {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": format!(
-                        "Insufficient inventory for product {}. Available: {}, Requested: {}",
-                        product.title, inventory_qty, item.quantity
-                    )
-                })),
-            ));
-        }
-
-        let item_subtotal = product.price * Decimal::from(item.quantity);
+        let unit_price = calculator.customer_unit_price(product.id, None, item.quantity, product.price, None);
+        let item_subtotal = unit_price * Decimal::from(item.quantity);
         subtotal += item_subtotal;
 
         // Build taxable item for tax calculation
@@ -297,7 +329,7 @@ pub async fn create_order(
             id: Uuid::new_v4(),
             product_id: product.id,
             quantity: item.quantity,
-            unit_price: product.price,
+            unit_price,
             total_price: item_subtotal,
             tax_category_id: None, // TODO: Get from product
             is_digital: false,     // TODO: Get from product
@@ -305,7 +337,7 @@ pub async fn create_order(
             sku: product.sku.clone(),
         });
 
-        order_items.push((product, item.quantity, item_subtotal));
+        order_items.push((product, item.quantity, unit_price, item_subtotal));
     }
 
     // Calculate tax using TaxService if shipping address is provided
@@ -351,7 +383,7 @@ pub async fn create_order(
 
         // Calculate shipping cost
         let package = rcommerce_core::shipping::Package {
-            weight: Decimal::from_str_exact("0.5").unwrap() * Decimal::from(order_items.iter().map(|(_, qty, _)| qty).sum::<i32>()),
+            weight: Decimal::from_str_exact("0.5").unwrap() * Decimal::from(order_items.iter().map(|(_, qty, _, _)| qty).sum::<i32>()),
             weight_unit: "kg".to_string(),
             length: Some(Decimal::from(30)),
             width: Some(Decimal::from(20)),
@@ -411,13 +443,32 @@ pub async fn create_order(
 
     let total = subtotal + tax_total + shipping_total;
 
-    // Generate order number
-    let order_number = generate_order_number().await;
-
     let order_id = Uuid::new_v4();
 
+    // The order, its items, its stock reservations and its order.created
+    // event are written together
+    let create_failed = |e: sqlx::Error| {
+        tracing::error!("Failed to create order: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Failed to create order"})),
+        )
+    };
+    let mut tx = state.db.pool().begin().await.map_err(create_failed)?;
+
+    let order_number = match state.order_numbers.next(&mut tx, "web", chrono::Utc::now()).await {
+        Ok(number) => number,
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Failed to create order"})),
+            ));
+        }
+    };
+
     // Create order
-    let order = match sqlx::query_as::<_, rcommerce_core::models::Order>(
+    let order = sqlx::query_as::<_, rcommerce_core::models::Order>(
         r#"
         INSERT INTO orders (
             id, order_number, customer_id, email,
@@ -443,24 +494,17 @@ pub async fn create_order(
     .bind(shipping_total)
     .bind(total)
     .bind(request.notes)
-    .fetch_one(state.db.pool())
+    .fetch_one(&mut *tx)
     .await
-    {
-        Ok(o) => o,
-        Err(e) => {
-            tracing::error!("Failed to create order: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": "Failed to create order"})),
-            ));
-        }
-    };
+    .map_err(create_failed)?;
 
-    // Create order items and update inventory
-    for (product, quantity, item_total) in order_items {
+    // Create order items
+    let mut taken: Vec<(Uuid, Option<Uuid>, i32)> = Vec::new();
+    let mut titles: Vec<(Uuid, String)> = Vec::new();
+    for (product, quantity, unit_price, item_total) in order_items {
         let item_id = Uuid::new_v4();
 
-        if let Err(e) = sqlx::query(
+        sqlx::query(
             r#"
             INSERT INTO order_items (
                 id, order_id, product_id, variant_id,
@@ -475,29 +519,82 @@ pub async fn create_order(
         .bind(product.id)
         .bind(None::<Uuid>) // variant_id
         .bind(quantity)
-        .bind(product.price)
+        .bind(unit_price)
         .bind(item_total)
         .bind(&product.sku)
         .bind(&product.title)
         .bind(None::<String>) // variant_title
-        .execute(state.db.pool())
+        .execute(&mut *tx)
         .await
-        {
-            tracing::error!("Failed to create order item: {}", e);
-        }
+        .map_err(create_failed)?;
 
-        // Update inventory on product
-        if let Err(e) = sqlx::query(
-            "UPDATE products SET inventory_quantity = inventory_quantity - $1, updated_at = NOW() WHERE id = $2"
-        )
-        .bind(quantity)
-        .bind(product.id)
-        .execute(state.db.pool())
-        .await {
-            tracing::error!("Failed to update inventory: {}", e);
+        taken.push((product.id, None, quantity));
+        titles.push((product.id, product.title.clone()));
+    }
+
+    // Reserve the stock, bundles by their components, in product order so
+    // concurrent orders lock stock levels in the same order; an item out of
+    // stock rolls the whole order back instead of overselling
+    let product_ids: Vec<Uuid> = taken.iter().map(|(product_id, _, _)| *product_id).collect();
+    let components = sqlx::query_as::<_, (Uuid, Uuid, i32, String)>(
+        r#"
+        SELECT bc.bundle_product_id, bc.component_product_id, bc.quantity, c.title
+        FROM bundle_components bc
+        JOIN products b ON b.id = bc.bundle_product_id AND b.product_type = 'bundle'
+        JOIN products c ON c.id = bc.component_product_id
+        WHERE bc.bundle_product_id = ANY($1) AND NOT bc.is_optional
+        "#
+    )
+    .bind(&product_ids)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    titles.extend(components.iter().map(|(_, component_id, _, title)| (*component_id, title.clone())));
+    let components: Vec<(Uuid, Uuid, i32)> =
+        components.into_iter().map(|(bundle_id, component_id, quantity, _)| (bundle_id, component_id, quantity)).collect();
+    for (product_id, variant_id, quantity) in stock_lines(&taken, &components) {
+        match state.inventory.reserve_for_order(&mut tx, order_id, product_id, variant_id, quantity).await {
+            Ok(_) => {}
+            Err(rcommerce_core::Error::Validation(msg)) => {
+                let title = titles
+                    .iter()
+                    .find(|(id, _)| *id == product_id)
+                    .map_or_else(|| product_id.to_string(), |(_, title)| title.clone());
+                return Err((
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({
+                        "error": format!("Insufficient inventory for product {}: {}", title, msg)
+                    })),
+                ));
+            }
+            Err(e) => {
+                tracing::error!("Failed to reserve stock: {}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": "Failed to create order"})),
+                ));
+            }
         }
     }
 
+    let event = DomainEvent::OrderCreated(OrderCreated {
+        order_id: order.id,
+        order_number: order.order_number.clone(),
+        customer_id: order.customer_id,
+        email: order.email.clone(),
+        total: order.total,
+        currency: order.currency.to_string(),
+        status: status_label(&order.status),
+    });
+    if let Err(e) = enqueue_event(&mut tx, &event).await {
+        tracing::error!("{}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Failed to create order"})),
+        ));
+    }
+    tx.commit().await.map_err(create_failed)?;
+
     // Get items for response
     let items = match sqlx::query_as::<_, rcommerce_core::models::OrderItem>(
         "SELECT * FROM order_items WHERE order_id = $1",
@@ -547,12 +644,192 @@ pub async fn create_order(
     Ok((StatusCode::CREATED, Json(response)))
 }
 
-/// Generate unique order number
-async fn generate_order_number() -> String {
-    let prefix = "ORD";
-    let timestamp = chrono::Utc::now().timestamp();
-    let random = rand::random::<u16>();
-    format!("{}{}{:05}", prefix, timestamp % 100000, random % 10000)
+/// Orders listed per page in the admin API, at most
+const ADMIN_PAGE_LIMIT: i64 = 100;
+
+/// Query parameters for browsing orders as staff
+#[derive(Debug, Deserialize)]
+pub struct AdminOrderQuery {
+    /// Order status, e.g. `processing`
+    pub status: Option<String>,
+    /// Part of an order number or customer email
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page (empty for the first); replaces
+    /// `offset`, which gets slow deep into the orders table
+    pub cursor: Option<String>,
+}
+
+/// A payment of an order, as shown to staff
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminOrderPayment {
+    pub id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    pub refunded: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// GET /api/v1/admin/orders
+pub async fn admin_list_orders(
+    State(state): State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<AdminOrderQuery>,
+) -> Result<Json<serde_json::Value>, rcommerce_core::Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, ADMIN_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let status = query.status.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let search = query
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(|q| format!("%{}%", q));
+
+    let filter = r#"
+        WHERE deleted_at IS NULL
+          AND ($1::TEXT IS NULL OR status::TEXT = $1)
+          AND ($2::TEXT IS NULL OR order_number ILIKE $2 OR email ILIKE $2)
+    "#;
+
+    if query.cursor.is_some() {
+        let cursor = Cursor::<Uuid>::parse(query.cursor.as_deref())?;
+        let rows = sqlx::query_as::<_, rcommerce_core::models::Order>(&format!(
+            "SELECT * FROM orders {} AND ($3::TIMESTAMPTZ IS NULL OR {}) ORDER BY created_at DESC, id DESC LIMIT $5",
+            filter,
+            after_cursor_sql("created_at", "id", 3)
+        ))
+        .bind(status)
+        .bind(search.as_deref())
+        .bind(cursor.map(|c| c.created_at))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(state.db.pool())
+        .await
+        .map_err(|e| rcommerce_core::Error::Other(format!("Failed to list orders: {}", e)))?;
+        let page = CursorPage::from_rows(rows, limit, |order| Cursor::new(order.created_at, order.id));
+        let orders: Vec<OrderResponse> = page
+            .items
+            .into_iter()
+            .map(|order| order_response(order, Vec::new()))
+            .collect();
+        return Ok(Json(serde_json::json!({
+            "orders": orders,
+            "meta": {
+                "limit": limit,
+                "next_cursor": page.next_cursor,
+            }
+        })));
+    }
+
+    let orders = sqlx::query_as::<_, rcommerce_core::models::Order>(&format!(
+        "SELECT * FROM orders {} ORDER BY created_at DESC LIMIT $3 OFFSET $4",
+        filter
+    ))
+    .bind(status)
+    .bind(search.as_deref())
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| rcommerce_core::Error::Other(format!("Failed to list orders: {}", e)))?;
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM orders {}", filter))
+        .bind(status)
+        .bind(search.as_deref())
+        .fetch_one(state.db.pool())
+        .await
+        .map_err(|e| rcommerce_core::Error::Other(format!("Failed to count orders: {}", e)))?;
+
+    let orders: Vec<OrderResponse> = orders
+        .into_iter()
+        .map(|order| order_response(order, Vec::new()))
+        .collect();
+    Ok(Json(serde_json::json!({
+        "orders": orders,
+        "meta": {
+            "total": total,
+            "limit": limit,
+            "offset": offset,
+        }
+    })))
+}
+
+/// GET /api/v1/admin/orders/:id
+///
+/// The order with its items and payments, so staff can refund a payment
+/// (`POST /api/v1/admin/payments/:id/refund`).
+pub async fn admin_get_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Json(order) = get_order(State(state.clone()), Path(id)).await?;
+    let payments = sqlx::query_as::<_, AdminOrderPayment>(
+        r#"
+        SELECT p.id, p.gateway, p.gateway_payment_id, p.amount, p.currency::text AS currency,
+               p.status::text AS status,
+               COALESCE((SELECT SUM(r.amount) FROM refunds r
+                         WHERE r.payment_id = p.id AND r.status = 'refunded'), 0) AS refunded,
+               p.created_at
+        FROM payments p
+        WHERE p.order_id = $1
+        ORDER BY p.created_at
+        "#,
+    )
+    .bind(id)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get payments of order {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Database error"})),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "order": order,
+        "payments": payments,
+    })))
+}
+
+/// GET /api/v1/admin/orders/by-number/:order_number
+///
+/// Like `GET /api/v1/admin/orders/:id`, for the order with this number.
+pub async fn admin_get_order_by_number(
+    State(state): State<AppState>,
+    Path(order_number): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM orders WHERE LOWER(order_number) = LOWER($1) AND deleted_at IS NULL"
+    )
+    .bind(order_number.trim())
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to find order {}: {}", order_number, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Database error"})),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Order not found"})),
+        )
+    })?;
+    admin_get_order(State(state), Path(id)).await
+}
+
+/// Staff order routes (mounted under the admin routes)
+pub fn admin_router() -> Router<AppState> {
+    axum::Router::new()
+        .route("/admin/orders", get(admin_list_orders))
+        .route("/admin/orders/:id", get(admin_get_order))
+        .route("/admin/orders/by-number/:order_number", get(admin_get_order_by_number))
 }
 
 /// Router for order routes
@@ -0,0 +1,83 @@
+//! Order Archive API Routes
+//!
+//! Admin-only endpoints for order auto-archival:
+//! - GET  /api/v1/admin/orders/archive     - Archive size, settings and orders currently eligible
+//! - POST /api/v1/admin/orders/archive/run - Archive eligible orders now (`?dry_run=true` only counts them)
+//!
+//! Archived orders stay readable through GET /api/v1/orders/:id and the
+//! customer invoice endpoints.
+
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::jobs::spawn_singleton;
+use rcommerce_core::models::OrderArchiveReport;
+use rcommerce_core::repository::OrderArchiveRepository;
+use rcommerce_core::Error;
+
+/// Query parameters for a manual run
+#[derive(Debug, Default, Deserialize)]
+pub struct RunQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// GET /api/v1/admin/orders/archive
+pub async fn get_archive(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let archive = &state.order_archive;
+    let config = archive.config();
+    let cutoff = archive.cutoff(Utc::now());
+    let stats = archive.repository().stats().await?;
+    let eligible = archive.repository().count_archivable(cutoff, &config.statuses).await?;
+
+    Ok(Json(serde_json::json!({
+        "stats": stats,
+        "eligible": eligible,
+        "cutoff": cutoff,
+        "config": config,
+    })))
+}
+
+/// POST /api/v1/admin/orders/archive/run
+pub async fn run_archive(
+    State(state): State<AppState>,
+    Query(query): Query<RunQuery>,
+) -> Result<Json<OrderArchiveReport>, Error> {
+    Ok(Json(state.order_archive.run(query.dry_run).await?))
+}
+
+/// Spawn periodic archival when `[order_archive] enabled = true`, on the
+/// instance leading `order_archive`
+pub fn spawn_archiver(state: &AppState) {
+    let archive = state.order_archive.clone();
+    if !archive.config().enabled {
+        return;
+    }
+
+    let interval = std::time::Duration::from_secs(archive.config().interval_secs.max(60));
+    spawn_singleton(state.locks.clone(), "order_archive", interval, move || {
+        let archive = archive.clone();
+        async move {
+            match archive.run(false).await {
+                Ok(report) if report.archived > 0 => {
+                    tracing::info!("Archived {} orders finished before {:?}", report.archived, report.cutoff);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Order archival failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Router for order archive routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/orders/archive", get(get_archive))
+        .route("/admin/orders/archive/run", post(run_archive))
+}
@@ -0,0 +1,64 @@
+//! Table Partition API Routes
+//!
+//! Admin-only endpoints for the monthly partitions of notifications, order
+//! events, tax transactions and the order archive:
+//! - GET  /api/v1/admin/partitions        - List partitions with their bounds and row estimates
+//! - POST /api/v1/admin/partitions/ensure - Create missing partitions for the coming months now
+
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+
+use std::sync::Arc;
+
+use crate::state::AppState;
+use rcommerce_core::jobs::spawn_singleton;
+use rcommerce_core::{Error, PartitionManager, PARTITION_MONTHS_AHEAD};
+
+/// Partitions are topped up once a day
+const MAINTENANCE_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+fn partitions(state: &AppState) -> PartitionManager {
+    PartitionManager::new(state.db.pool().clone())
+}
+
+/// GET /api/v1/admin/partitions
+pub async fn list_partitions(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let partitions = partitions(&state).list_partitions().await?;
+
+    Ok(Json(serde_json::json!({
+        "partitions": partitions,
+        "months_ahead": PARTITION_MONTHS_AHEAD,
+    })))
+}
+
+/// POST /api/v1/admin/partitions/ensure
+pub async fn ensure_partitions(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let created = partitions(&state).ensure_partitions(PARTITION_MONTHS_AHEAD).await?;
+
+    Ok(Json(serde_json::json!({ "created": created })))
+}
+
+/// Spawn the daily job creating next months' partitions, on the instance
+/// leading `partition_maintenance`
+pub fn spawn_maintenance(state: &AppState) {
+    let manager = Arc::new(partitions(state));
+    let interval = std::time::Duration::from_secs(MAINTENANCE_INTERVAL_SECS);
+    spawn_singleton(state.locks.clone(), "partition_maintenance", interval, move || {
+        let manager = manager.clone();
+        async move {
+            if let Err(e) = manager.ensure_partitions(PARTITION_MONTHS_AHEAD).await {
+                tracing::error!("Partition maintenance failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Router for partition routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/partitions", get(list_partitions))
+        .route("/admin/partitions/ensure", post(ensure_partitions))
+}
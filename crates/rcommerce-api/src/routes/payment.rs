@@ -2,18 +2,39 @@
 //!
 //! Provides unified endpoints for all payment operations regardless of gateway.
 //! Frontend never communicates directly with payment providers.
+//!
+//! Each payment started here gets a payment session. A card payment needing
+//! 3-D Secure answers `requires_action` with a `three_d_secure` challenge:
+//! the storefront redirects to its `url` (or shows it in an iframe, posting
+//! `form` when present). The gateway sends the customer back to
+//! `/api/v1/payments/sessions/:id/return`, which completes the payment and
+//! redirects to the storefront's `return_url`; storefronts can also poll
+//! `GET /api/v1/payments/sessions/:id`.
+//!
+//! With `payment.capture.method = "manual"` payments are only authorized;
+//! staff capture them (in part, several times) or void them under
+//! `/api/v1/admin/payments/:id`, or shipments capture them on fulfillment.
+
+use std::collections::HashMap;
 
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post},
+    extract::{Extension, Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use crate::middleware::JwtAuth;
 use crate::state::AppState;
+use rcommerce_core::events::{DomainEvent, PaymentUpdated};
+use rcommerce_core::models::{
+    CapturePaymentRequest, PaymentAuthorization, PaymentCapture, PaymentSession, PaymentSessionStatus,
+};
 use rcommerce_core::payment::agnostic::*;
+use rcommerce_core::repository::enqueue_event;
 use rcommerce_core::Error;
 
 /// Get available payment methods for a checkout
@@ -53,6 +74,16 @@ pub async fn get_payment_methods(
         std::collections::HashMap::new();
 
     for (gateway_id, method) in methods {
+        // Wallets turned off in [payment.wallets]
+        let wallet_enabled = match method.method_type {
+            PaymentMethodType::ApplePay => state.wallets.apple_pay.enabled,
+            PaymentMethodType::GooglePay => state.wallets.google_pay.enabled,
+            _ => true,
+        };
+        if !wallet_enabled {
+            continue;
+        }
+
         gateway_methods
             .entry(gateway_id)
             .or_default()
@@ -96,13 +127,28 @@ pub struct InitiatePaymentApiRequest {
     pub description: String,
     #[serde(default)]
     pub idempotency_key: Option<String>,
+    /// Storefront page to return to after a 3-D Secure challenge; its host
+    /// must be in `payment.three_d_secure.allowed_return_hosts`
+    #[serde(default)]
+    pub return_url: Option<String>,
+    /// How the storefront shows a challenge (`redirect` or `iframe`)
+    #[serde(default)]
+    pub challenge_display: ChallengeDisplay,
+}
+
+/// Initiated payment and the session following it
+#[derive(Debug, Serialize)]
+pub struct InitiatePaymentApiResponse {
+    pub session_id: Uuid,
+    #[serde(flatten)]
+    pub payment: InitiatePaymentResponse,
 }
 
 /// Initiate a payment
 pub async fn initiate_payment(
     State(state): State<AppState>,
     Json(request): Json<InitiatePaymentApiRequest>,
-) -> Result<Json<InitiatePaymentResponse>, Error> {
+) -> Result<Json<InitiatePaymentApiResponse>, Error> {
     // Parse amount
     let amount = request
         .amount
@@ -118,16 +164,10 @@ pub async fn initiate_payment(
         info!("Processing payment with idempotency key: {}", key);
     }
 
-    // Get the gateway
-    let gateway = state
-        .payment_service
-        .get_gateway(Some(&request.gateway_id))
-        .ok_or_else(|| Error::validation(format!("Gateway '{}' not found", request.gateway_id)))?;
-
     // Build the initiate payment request
     let initiate_request = InitiatePaymentRequest {
         amount,
-        currency: request.currency,
+        currency: request.currency.clone(),
         payment_method_type: request.payment_method_type,
         order_id,
         customer_id: None, // Could be extracted from auth context
@@ -142,10 +182,21 @@ pub async fn initiate_payment(
             "order_id": order_id.to_string(),
             "idempotency_key": request.idempotency_key,
         }),
+        return_url: None,
+        // Set by the payment session from payment.capture
+        capture_method: CaptureMethod::Automatic,
     };
 
-    // Process the payment
-    let response = gateway.initiate_payment(initiate_request).await?;
+    // Process the payment; the session records a succeeded one with its receipt
+    let (session, response) = state
+        .payment_sessions
+        .initiate(
+            &request.gateway_id,
+            initiate_request,
+            request.return_url,
+            request.challenge_display,
+        )
+        .await?;
 
     // Log the result
     match &response {
@@ -167,7 +218,10 @@ pub async fn initiate_payment(
         }
     }
 
-    Ok(Json(response))
+    Ok(Json(InitiatePaymentApiResponse {
+        session_id: session.id,
+        payment: response,
+    }))
 }
 
 /// Complete a payment action (3DS, redirect return, etc.)
@@ -182,14 +236,6 @@ pub async fn complete_payment_action(
     Path(payment_id): Path<String>,
     Json(request): Json<CompletePaymentActionApiRequest>,
 ) -> Result<Json<CompletePaymentActionResponse>, Error> {
-    // Get the gateway - we need to determine which gateway this payment belongs to
-    // For now, we'll use the default gateway from the payment service
-    // In production, you'd look up the payment in the database to find the gateway
-    let gateway = state
-        .payment_service
-        .get_gateway(None)
-        .ok_or_else(|| Error::payment_error("No payment gateway configured"))?;
-
     // Build the complete action request
     let complete_request = CompletePaymentActionRequest {
         payment_id: payment_id.clone(),
@@ -197,8 +243,8 @@ pub async fn complete_payment_action(
         action_data: request.action_data,
     };
 
-    // Complete the payment action
-    let response = gateway.complete_payment_action(complete_request).await?;
+    // Complete the payment action with the gateway that took the payment
+    let response = state.payment_sessions.complete_payment(complete_request).await?;
 
     // Log the result
     match &response {
@@ -234,6 +280,8 @@ pub struct PaymentStatusResponse {
     pub transaction_id: Option<String>,
     pub created_at: String,
     pub updated_at: Option<String>,
+    /// Session following the payment, with any pending challenge
+    pub session: Option<PaymentSession>,
 }
 
 /// Get payment status
@@ -241,7 +289,22 @@ pub async fn get_payment_status(
     State(state): State<AppState>,
     Path(payment_id): Path<String>,
 ) -> Result<Json<PaymentStatusResponse>, Error> {
-    // Get the gateway
+    if let Some(session) = state.payment_sessions.poll_payment(&payment_id).await? {
+        info!("Retrieved payment status for {}: {}", payment_id, session.status.as_str());
+        return Ok(Json(PaymentStatusResponse {
+            payment_id,
+            status: payment_status(session.status),
+            amount: session.amount.to_string(),
+            currency: session.currency.clone(),
+            gateway_id: session.gateway.clone(),
+            transaction_id: session.gateway_payment_id.clone(),
+            created_at: session.created_at.to_rfc3339(),
+            updated_at: Some(session.updated_at.to_rfc3339()),
+            session: Some(session),
+        }));
+    }
+
+    // Payments started before sessions were kept
     let gateway = state
         .payment_service
         .get_gateway(None)
@@ -262,9 +325,55 @@ pub async fn get_payment_status(
         transaction_id: None, // Would come from database
         created_at: chrono::Utc::now().to_rfc3339(), // Would come from database
         updated_at: None,
+        session: None,
     }))
 }
 
+/// Gateway payment status of a session status
+fn payment_status(status: PaymentSessionStatus) -> PaymentStatus {
+    match status {
+        PaymentSessionStatus::Pending => PaymentStatus::Pending,
+        PaymentSessionStatus::RequiresAction => PaymentStatus::RequiresAction,
+        PaymentSessionStatus::Processing => PaymentStatus::Processing,
+        PaymentSessionStatus::Authorized => PaymentStatus::Authorized,
+        PaymentSessionStatus::Succeeded => PaymentStatus::Succeeded,
+        PaymentSessionStatus::Failed => PaymentStatus::Failed,
+        PaymentSessionStatus::Cancelled | PaymentSessionStatus::Expired => PaymentStatus::Cancelled,
+    }
+}
+
+/// GET /api/v1/payments/sessions/:id
+///
+/// The session brought up to date with its gateway; storefronts poll this
+/// while a challenge is open.
+pub async fn get_payment_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PaymentSession>, Error> {
+    Ok(Json(state.payment_sessions.poll(id).await?))
+}
+
+/// GET|POST /api/v1/payments/sessions/:id/return
+///
+/// Where the gateway sends the customer after a 3-D Secure challenge. The
+/// payment is completed with the query parameters the gateway added, then
+/// the customer is redirected to the storefront's return URL with
+/// `payment_session` and `status`; without one the session is returned.
+pub async fn payment_session_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, Error> {
+    let action_data = serde_json::to_value(params).unwrap_or_default();
+    let session = state.payment_sessions.complete(id, action_data).await?;
+    info!("Payment session {} returned from challenge: {}", id, session.status.as_str());
+
+    match session.return_redirect() {
+        Some(url) => Ok(Redirect::to(&url).into_response()),
+        None => Ok(Json(session).into_response()),
+    }
+}
+
 /// Refund a payment
 #[derive(Debug, Deserialize)]
 pub struct RefundRequest {
@@ -305,6 +414,222 @@ pub async fn refund_payment(
     Ok(Json(response))
 }
 
+/// Refund issued by staff against a recorded payment
+#[derive(Debug, Deserialize)]
+pub struct AdminRefundRequest {
+    /// Defaults to what is left to refund
+    pub amount: Option<rust_decimal::Decimal>,
+    pub reason: String,
+}
+
+/// A recorded payment and how much of it was refunded
+#[derive(Debug, sqlx::FromRow)]
+struct RefundablePayment {
+    order_id: Uuid,
+    gateway: String,
+    gateway_payment_id: Option<String>,
+    amount: rust_decimal::Decimal,
+    currency: String,
+    status: String,
+    refunded: rust_decimal::Decimal,
+}
+
+/// POST /api/v1/admin/payments/:id/refund
+///
+/// Refunds part or all of a recorded payment through its gateway and
+/// records the refund; the order is marked refunded once nothing is left.
+pub async fn admin_refund_payment(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AdminRefundRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let reason = request.reason.trim();
+    if reason.is_empty() {
+        return Err(Error::validation("reason is required"));
+    }
+    let payment = sqlx::query_as::<_, RefundablePayment>(
+        r#"
+        SELECT p.order_id, p.gateway, p.gateway_payment_id,
+               CASE WHEN p.authorized_amount IS NULL THEN p.amount ELSE p.captured_amount END AS amount,
+               p.currency::text AS currency,
+               p.status::text AS status,
+               COALESCE((SELECT SUM(r.amount) FROM refunds r
+                         WHERE r.payment_id = p.id AND r.status = 'refunded'), 0) AS refunded
+        FROM payments p
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| Error::Other(format!("Failed to get payment: {}", e)))?
+    .ok_or_else(|| Error::not_found(format!("Payment {} not found", id)))?;
+
+    if !matches!(payment.status.as_str(), "paid" | "refunded") {
+        return Err(Error::validation(format!("Payment {} is {} and cannot be refunded", id, payment.status)));
+    }
+    let remaining = payment.amount - payment.refunded;
+    let amount = request.amount.unwrap_or(remaining);
+    if amount <= rust_decimal::Decimal::ZERO {
+        return Err(Error::validation("Nothing left to refund on this payment"));
+    }
+    if amount > remaining {
+        return Err(Error::validation(format!(
+            "Only {} {} is left to refund on this payment",
+            remaining, payment.currency
+        )));
+    }
+
+    let gateway = state
+        .payment_service
+        .get_gateway(Some(&payment.gateway))
+        .ok_or_else(|| Error::payment_error(format!("Payment gateway '{}' is not configured", payment.gateway)))?;
+    let gateway_payment_id = payment
+        .gateway_payment_id
+        .as_deref()
+        .ok_or_else(|| Error::payment_error("The payment has no gateway reference"))?;
+    let response = gateway.refund_payment(gateway_payment_id, Some(amount), reason).await?;
+    if response.status == RefundStatus::Failed {
+        return Err(Error::payment_error(format!("Refund of payment {} failed", id)));
+    }
+
+    let fully_refunded = amount == remaining;
+    let mut tx = state
+        .db
+        .pool()
+        .begin()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+    let refund_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO refunds (payment_id, order_id, amount, currency, reason, status, gateway_refund_id)
+        VALUES ($1, $2, $3, $4::currency, $5, 'refunded', $6)
+        RETURNING id
+        "#,
+    )
+    .bind(id)
+    .bind(payment.order_id)
+    .bind(amount)
+    .bind(&payment.currency)
+    .bind(reason)
+    .bind(&response.refund_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to record refund: {}", e)))?;
+    if fully_refunded {
+        sqlx::query("UPDATE payments SET status = 'refunded', updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update payment: {}", e)))?;
+        sqlx::query("UPDATE orders SET payment_status = 'refunded', updated_at = NOW() WHERE id = $1")
+            .bind(payment.order_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update order: {}", e)))?;
+        let event = DomainEvent::PaymentUpdated(PaymentUpdated {
+            payment_id: Some(id),
+            order_id: payment.order_id,
+            status: "refunded".to_string(),
+            previous_status: Some(payment.status.clone()),
+            amount: payment.amount,
+            currency: payment.currency.clone(),
+            gateway: Some(payment.gateway.clone()),
+        });
+        enqueue_event(&mut tx, &event).await?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to commit refund: {}", e)))?;
+
+    info!(
+        "Refunded {} {} of payment {} (refund {}, gateway refund {})",
+        amount, payment.currency, id, refund_id, response.refund_id
+    );
+    Ok(Json(serde_json::json!({
+        "refund_id": refund_id,
+        "gateway_refund_id": response.refund_id,
+        "amount": amount,
+        "currency": payment.currency,
+        "status": response.status,
+        "remaining": remaining - amount,
+    })))
+}
+
+/// An authorization and its captures
+#[derive(Debug, Serialize)]
+pub struct PaymentCapturesResponse {
+    pub authorization: PaymentAuthorization,
+    pub remaining: rust_decimal::Decimal,
+    pub captures: Vec<PaymentCapture>,
+}
+
+/// GET /api/v1/admin/payments/:id/captures
+pub async fn admin_list_captures(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PaymentCapturesResponse>, Error> {
+    let authorization = state.payment_captures.authorization(id).await?;
+    let captures = state.payment_captures.captures(id).await?;
+    Ok(Json(PaymentCapturesResponse {
+        remaining: authorization.remaining(),
+        authorization,
+        captures,
+    }))
+}
+
+/// POST /api/v1/admin/payments/:id/capture
+///
+/// Captures part or the rest of an authorized payment. `final_capture`
+/// releases what is left; capturing everything left implies it.
+pub async fn admin_capture_payment(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    auth: Option<Extension<JwtAuth>>,
+    Json(request): Json<CapturePaymentRequest>,
+) -> Result<Json<PaymentCapture>, Error> {
+    let captured_by = auth.map(|Extension(auth)| auth.customer_id);
+    let capture = state.payment_captures.capture(id, request, captured_by).await?;
+    info!(
+        "Captured {} {} of payment {} (final: {})",
+        capture.amount, capture.currency, id, capture.final_capture
+    );
+    Ok(Json(capture))
+}
+
+/// POST /api/v1/admin/payments/:id/void
+///
+/// Releases what is left of an authorization; captures stay paid.
+pub async fn admin_void_payment(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PaymentAuthorization>, Error> {
+    let authorization = state.payment_captures.void(id).await?;
+    info!("Voided authorization of payment {}", id);
+    Ok(Json(authorization))
+}
+
+/// Capture-on-fulfillment setting of an order
+#[derive(Debug, Deserialize)]
+pub struct CaptureOnFulfillmentRequest {
+    /// None follows `payment.capture.on_fulfillment`
+    pub enabled: Option<bool>,
+}
+
+/// PUT /api/v1/admin/orders/:id/capture-on-fulfillment
+pub async fn admin_set_capture_on_fulfillment(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CaptureOnFulfillmentRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    state.payment_captures.set_capture_on_fulfillment(id, request.enabled).await?;
+    Ok(Json(serde_json::json!({
+        "order_id": id,
+        "capture_on_fulfillment": request.enabled,
+        "effective": request.enabled.unwrap_or(state.payment_captures.config().on_fulfillment),
+    })))
+}
+
 /// Save a payment method for future use
 #[derive(Debug, Deserialize)]
 pub struct SavePaymentMethodRequest {
@@ -423,6 +748,14 @@ pub async fn handle_webhook(
         gateway_id, event.event_type, event.payment_id
     );
 
+    // Checkout sessions and payment links update their order
+    if let Some(checkout) = state.hosted_checkouts.handle_webhook_event(&gateway_id, &event).await? {
+        info!(
+            "Hosted checkout {} for order {} is {:?}",
+            checkout.id, checkout.order_id, checkout.status
+        );
+    }
+
     // Handle different event types
     match event.event_type {
         WebhookEventType::PaymentSucceeded => {
@@ -454,6 +787,24 @@ pub fn router() -> Router<AppState> {
     payment_routes()
 }
 
+/// Challenge return routes, reached by customers coming back from their bank
+pub fn public_router() -> Router<AppState> {
+    Router::new().route(
+        "/payments/sessions/:id/return",
+        get(payment_session_return).post(payment_session_return),
+    )
+}
+
+/// Staff payment routes (mounted under the admin routes)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/payments/:id/refund", post(admin_refund_payment))
+        .route("/admin/payments/:id/captures", get(admin_list_captures))
+        .route("/admin/payments/:id/capture", post(admin_capture_payment))
+        .route("/admin/payments/:id/void", post(admin_void_payment))
+        .route("/admin/orders/:id/capture-on-fulfillment", put(admin_set_capture_on_fulfillment))
+}
+
 /// Payment routes that require authentication
 pub fn payment_routes() -> Router<AppState> {
     Router::new()
@@ -463,6 +814,8 @@ pub fn payment_routes() -> Router<AppState> {
         .route("/payments", post(initiate_payment))
         // Get payment status
         .route("/payments/:payment_id", get(get_payment_status))
+        // Poll a payment session
+        .route("/payments/sessions/:id", get(get_payment_session))
         // Complete payment action (3DS, redirect)
         .route(
             "/payments/:payment_id/complete",
@@ -0,0 +1,84 @@
+//! Local Pickup Routes
+//!
+//! Inventory locations with pickup enabled are offered at checkout as free
+//! `pickup` shipping rates when they have the cart in stock:
+//! - GET /api/v1/pickup/locations                    - Pickup locations with hours and instructions
+//! - GET /api/v1/products/:id/pickup?variant_id=...  - Stock of a product at each pickup location
+//! - PUT /api/v1/admin/pickup/locations/:id          - Offer pickup at an inventory location
+//! - POST /api/v1/admin/shipments/:id/ready-for-pickup - Pickup order packed; emails the customer
+//! - POST /api/v1/admin/shipments/:id/picked-up      - Pickup order collected; emails the customer
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::shipping::{PickupLocation, PickupShipment, UpdatePickupLocation};
+use rcommerce_core::Error;
+
+/// Variant to check pickup stock of
+#[derive(Debug, Deserialize)]
+pub struct PickupAvailabilityQuery {
+    pub variant_id: Option<Uuid>,
+}
+
+/// GET /api/v1/pickup/locations
+pub async fn list_locations(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let locations = state.pickup.locations().await?;
+    Ok(Json(json!({ "locations": locations })))
+}
+
+/// GET /api/v1/products/:id/pickup
+pub async fn product_availability(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Query(query): Query<PickupAvailabilityQuery>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let availability = state.pickup.availability(product_id, query.variant_id).await?;
+    Ok(Json(json!({ "product_id": product_id, "locations": availability })))
+}
+
+/// PUT /api/v1/admin/pickup/locations/:id
+pub async fn update_location(
+    State(state): State<AppState>,
+    Path(location_id): Path<Uuid>,
+    Json(request): Json<UpdatePickupLocation>,
+) -> Result<Json<PickupLocation>, Error> {
+    Ok(Json(state.pickup.update_location(location_id, request).await?))
+}
+
+/// POST /api/v1/admin/shipments/:id/ready-for-pickup
+pub async fn mark_ready(
+    State(state): State<AppState>,
+    Path(shipment_id): Path<Uuid>,
+) -> Result<Json<PickupShipment>, Error> {
+    Ok(Json(state.pickup.mark_ready(shipment_id).await?))
+}
+
+/// POST /api/v1/admin/shipments/:id/picked-up
+pub async fn mark_picked_up(
+    State(state): State<AppState>,
+    Path(shipment_id): Path<Uuid>,
+) -> Result<Json<PickupShipment>, Error> {
+    Ok(Json(state.pickup.mark_picked_up(shipment_id).await?))
+}
+
+/// Router for storefront pickup routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/pickup/locations", get(list_locations))
+        .route("/products/:id/pickup", get(product_availability))
+}
+
+/// Router for pickup admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/pickup/locations/:id", put(update_location))
+        .route("/admin/shipments/:id/ready-for-pickup", post(mark_ready))
+        .route("/admin/shipments/:id/picked-up", post(mark_picked_up))
+}
@@ -0,0 +1,147 @@
+//! Purchase Order Dispatch API Routes
+//!
+//! How placed purchase orders reach suppliers, their acknowledgments and
+//! the discrepancies found in acknowledgments and deliveries
+//! (`[purchasing]`):
+//! - GET    /api/v1/admin/suppliers/:id/dispatch                     - Get how orders reach the supplier
+//! - PUT    /api/v1/admin/suppliers/:id/dispatch                     - Email, SFTP (EDI-lite CSV) or manual
+//! - POST   /api/v1/admin/purchase-orders/:id/dispatch               - Send a placed order to the supplier now
+//! - POST   /api/v1/admin/purchase-orders/:id/acknowledge            - Record the supplier's acknowledgment
+//! - GET    /api/v1/admin/purchase-orders/:id/tracking               - Dispatch and ETA history, and discrepancies
+//! - GET    /api/v1/admin/purchase-order-discrepancies               - Discrepancies (`?open=false&supplier_id=...`)
+//! - POST   /api/v1/admin/purchase-order-discrepancies/:id/resolve   - Resolve a discrepancy
+//!
+//! Suppliers acknowledge emailed orders through the link in the email (the
+//! token authenticates them):
+//! - GET    /api/v1/purchase-orders/acknowledge/:token  - The order to acknowledge
+//! - POST   /api/v1/purchase-orders/acknowledge/:token  - Confirm quantities, costs, reference and ETA
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::inventory::{
+    AcknowledgePurchaseOrderRequest, Discrepancy, PurchaseOrderDetail, PurchaseOrderTracking,
+    ResolveDiscrepancyRequest, SaveDispatchSettingsRequest, SupplierDispatchSettings,
+};
+use rcommerce_core::Error;
+
+/// Query parameters for listing discrepancies
+#[derive(Debug, Deserialize)]
+pub struct ListDiscrepanciesQuery {
+    /// Only unresolved ones (default)
+    pub open: Option<bool>,
+    pub supplier_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/suppliers/:id/dispatch
+pub async fn get_dispatch_settings(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+) -> Result<Json<SupplierDispatchSettings>, Error> {
+    Ok(Json(state.po_dispatch.settings(supplier_id).await?))
+}
+
+/// PUT /api/v1/admin/suppliers/:id/dispatch
+pub async fn save_dispatch_settings(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+    Json(request): Json<SaveDispatchSettingsRequest>,
+) -> Result<Json<SupplierDispatchSettings>, Error> {
+    Ok(Json(state.po_dispatch.save_settings(supplier_id, request).await?))
+}
+
+/// POST /api/v1/admin/purchase-orders/:id/dispatch
+pub async fn dispatch_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseOrderTracking>, Error> {
+    Ok(Json(state.po_dispatch.dispatch(id).await?))
+}
+
+/// POST /api/v1/admin/purchase-orders/:id/acknowledge
+pub async fn acknowledge_purchase_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AcknowledgePurchaseOrderRequest>,
+) -> Result<Json<PurchaseOrderTracking>, Error> {
+    Ok(Json(state.po_dispatch.acknowledge(id, Some(auth.customer_id), request).await?))
+}
+
+/// GET /api/v1/admin/purchase-orders/:id/tracking
+pub async fn get_tracking(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseOrderTracking>, Error> {
+    Ok(Json(state.po_dispatch.tracking(id).await?))
+}
+
+/// GET /api/v1/admin/purchase-order-discrepancies
+pub async fn list_discrepancies(
+    State(state): State<AppState>,
+    Query(query): Query<ListDiscrepanciesQuery>,
+) -> Result<Json<Vec<Discrepancy>>, Error> {
+    Ok(Json(
+        state
+            .po_dispatch
+            .list_discrepancies(query.open.unwrap_or(true), query.supplier_id, query.limit.unwrap_or(50))
+            .await?,
+    ))
+}
+
+/// POST /api/v1/admin/purchase-order-discrepancies/:id/resolve
+pub async fn resolve_discrepancy(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ResolveDiscrepancyRequest>,
+) -> Result<Json<Discrepancy>, Error> {
+    Ok(Json(state.po_dispatch.resolve_discrepancy(id, auth.customer_id, request).await?))
+}
+
+/// GET /api/v1/purchase-orders/acknowledge/:token
+pub async fn get_for_acknowledgment(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    Ok(Json(state.po_dispatch.find_by_token(&token).await?))
+}
+
+/// POST /api/v1/purchase-orders/acknowledge/:token
+pub async fn acknowledge_by_token(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(request): Json<AcknowledgePurchaseOrderRequest>,
+) -> Result<Json<PurchaseOrderTracking>, Error> {
+    Ok(Json(state.po_dispatch.acknowledge_by_token(&token, request).await?))
+}
+
+/// Admin router for purchase order dispatch and discrepancies
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/suppliers/:id/dispatch",
+            get(get_dispatch_settings).put(save_dispatch_settings),
+        )
+        .route("/admin/purchase-orders/:id/dispatch", post(dispatch_purchase_order))
+        .route("/admin/purchase-orders/:id/acknowledge", post(acknowledge_purchase_order))
+        .route("/admin/purchase-orders/:id/tracking", get(get_tracking))
+        .route("/admin/purchase-order-discrepancies", get(list_discrepancies))
+        .route("/admin/purchase-order-discrepancies/:id/resolve", post(resolve_discrepancy))
+}
+
+/// Public router for suppliers acknowledging emailed orders
+pub fn public_router() -> Router<AppState> {
+    Router::new().route(
+        "/purchase-orders/acknowledge/:token",
+        get(get_for_acknowledgment).post(acknowledge_by_token),
+    )
+}
@@ -0,0 +1,73 @@
+//! Price History API Routes
+//!
+//! Admin-only endpoint for producing a product's price history during
+//! EU Omnibus Directive audits:
+//! - GET /api/v1/admin/products/:id/price-history - Recorded price changes, newest first
+//!
+//! Filters: `variant_id`, `from`, `to` (RFC 3339), `page`, `per_page`.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{PriceHistoryEntry, PriceHistoryFilter};
+use rcommerce_core::repository::{PostgresPriceHistoryRepository, PriceHistoryRepository};
+use rcommerce_core::Error;
+
+/// Query parameters for the price history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub variant_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    50
+}
+
+impl HistoryQuery {
+    fn limit_offset(&self) -> (i64, i64) {
+        let per_page = self.per_page.clamp(1, 500);
+        (per_page, (self.page.max(1) - 1) * per_page)
+    }
+}
+
+/// GET /api/v1/admin/products/:id/price-history
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<PriceHistoryEntry>>, Error> {
+    let (limit, offset) = query.limit_offset();
+    let filter = PriceHistoryFilter {
+        variant_id: query.variant_id,
+        from: query.from,
+        to: query.to,
+    };
+
+    let history = PostgresPriceHistoryRepository::new(state.db.pool().clone())
+        .history(product_id, &filter, limit, offset)
+        .await?;
+
+    Ok(Json(history))
+}
+
+/// Router for price history routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/admin/products/:id/price-history", get(get_price_history))
+}
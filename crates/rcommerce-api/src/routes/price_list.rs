@@ -0,0 +1,191 @@
+//! Price List API Routes
+//!
+//! Per-currency and per-region prices, with FX conversion for products a
+//! list does not cover:
+//! - GET    /api/v1/products/:id/price                        - Price in a currency (`?currency=&country=&variant_id=&quantity=`, defaults to the detected location); a signed-in customer's group prices apply
+//! - GET    /api/v1/admin/price-lists                         - List price lists
+//! - POST   /api/v1/admin/price-lists                         - Create a price list
+//! - GET    /api/v1/admin/price-lists/:id                     - Get a price list
+//! - PUT    /api/v1/admin/price-lists/:id                     - Update a price list
+//! - GET    /api/v1/admin/price-lists/:id/prices              - Prices on a list
+//! - PUT    /api/v1/admin/price-lists/:id/prices              - Set a product or variant price
+//! - DELETE /api/v1/admin/price-lists/:id/prices/:price_id    - Remove a price
+//! - GET    /api/v1/admin/fx/rate                             - Current rate from the configured provider (`?from=&to=`)
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreatePriceListRequest, Currency, PriceList, PriceListPrice, ResolvedPrice, SetPriceListPriceRequest,
+    UpdatePriceListRequest,
+};
+use rcommerce_core::repository::PriceListRepository;
+use rcommerce_core::services::GeoLocation;
+use rcommerce_core::Error;
+
+/// Query parameters for a product price
+#[derive(Debug, Deserialize)]
+pub struct PriceQuery {
+    pub variant_id: Option<Uuid>,
+    pub currency: Option<Currency>,
+    pub country: Option<String>,
+    /// Quantity for group price tiers (default 1)
+    pub quantity: Option<i32>,
+}
+
+/// Query parameters for an FX rate lookup
+#[derive(Debug, Deserialize)]
+pub struct RateQuery {
+    pub from: Currency,
+    pub to: Currency,
+}
+
+/// Current FX rate
+#[derive(Debug, Serialize)]
+pub struct FxRateResponse {
+    pub from: Currency,
+    pub to: Currency,
+    pub rate: Decimal,
+    pub provider: String,
+}
+
+/// GET /api/v1/products/:id/price
+pub async fn get_product_price(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Query(query): Query<PriceQuery>,
+    location: Option<Extension<GeoLocation>>,
+    auth: Option<Extension<JwtAuth>>,
+) -> Result<Json<ResolvedPrice>, Error> {
+    let location = location.map(|Extension(location)| location);
+    let currency = query
+        .currency
+        .or_else(|| location.as_ref().map(|l| l.currency))
+        .unwrap_or_else(|| state.price_lists.base_currency());
+    let country = query.country.or_else(|| location.and_then(|l| l.country));
+
+    let quantity = query.quantity.unwrap_or(1).max(1);
+
+    let pricing = match auth {
+        Some(Extension(auth)) => {
+            state
+                .customer_groups
+                .pricing_for_customer(auth.customer_id, &[product_id])
+                .await?
+        }
+        None => None,
+    };
+    let price = state
+        .price_lists
+        .resolve_customer_price(
+            product_id,
+            query.variant_id,
+            currency,
+            country.as_deref(),
+            quantity,
+            pricing.as_ref(),
+        )
+        .await?;
+    Ok(Json(price))
+}
+
+/// GET /api/v1/admin/price-lists
+pub async fn list_price_lists(State(state): State<AppState>) -> Result<Json<Vec<PriceList>>, Error> {
+    Ok(Json(state.price_lists.repository().list().await?))
+}
+
+/// POST /api/v1/admin/price-lists
+pub async fn create_price_list(
+    State(state): State<AppState>,
+    Json(request): Json<CreatePriceListRequest>,
+) -> Result<(StatusCode, Json<PriceList>), Error> {
+    let list = state.price_lists.create_list(request).await?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+/// GET /api/v1/admin/price-lists/:id
+pub async fn get_price_list(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PriceList>, Error> {
+    Ok(Json(state.price_lists.get_list(id).await?))
+}
+
+/// PUT /api/v1/admin/price-lists/:id
+pub async fn update_price_list(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePriceListRequest>,
+) -> Result<Json<PriceList>, Error> {
+    Ok(Json(state.price_lists.update_list(id, request).await?))
+}
+
+/// GET /api/v1/admin/price-lists/:id/prices
+pub async fn list_prices(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<PriceListPrice>>, Error> {
+    state.price_lists.get_list(id).await?;
+    Ok(Json(state.price_lists.repository().prices(id).await?))
+}
+
+/// PUT /api/v1/admin/price-lists/:id/prices
+pub async fn set_price(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SetPriceListPriceRequest>,
+) -> Result<Json<PriceListPrice>, Error> {
+    Ok(Json(state.price_lists.set_price(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/price-lists/:id/prices/:price_id
+pub async fn remove_price(
+    State(state): State<AppState>,
+    Path((id, price_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.price_lists.remove_price(id, price_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/fx/rate
+pub async fn get_fx_rate(
+    State(state): State<AppState>,
+    Query(query): Query<RateQuery>,
+) -> Result<Json<FxRateResponse>, Error> {
+    let fx = state.price_lists.fx();
+    let rate = fx
+        .rate(query.from, query.to)
+        .await?
+        .ok_or_else(|| Error::not_found(format!("No exchange rate from {} to {}", query.from, query.to)))?;
+
+    Ok(Json(FxRateResponse {
+        from: query.from,
+        to: query.to,
+        rate,
+        provider: fx.name().to_string(),
+    }))
+}
+
+/// Router for storefront price routes
+pub fn router() -> Router<AppState> {
+    Router::new().route("/products/:id/price", get(get_product_price))
+}
+
+/// Router for price list admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/price-lists", get(list_price_lists).post(create_price_list))
+        .route("/admin/price-lists/:id", get(get_price_list).put(update_price_list))
+        .route("/admin/price-lists/:id/prices", get(list_prices).put(set_price))
+        .route("/admin/price-lists/:id/prices/:price_id", delete(remove_price))
+        .route("/admin/fx/rate", get(get_fx_rate))
+}
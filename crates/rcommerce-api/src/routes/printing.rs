@@ -0,0 +1,122 @@
+//! Print Batch API Routes
+//!
+//! Warehouse print stations print the shipping labels or packing slips of
+//! many shipments at once as one PDF, laid out for a printer profile
+//! (`[printing]`):
+//! - GET  /api/v1/admin/printing/profiles        - Printer profiles
+//! - POST /api/v1/admin/print-batches            - Generate a batch (listed or unprinted shipments)
+//! - GET  /api/v1/admin/print-batches            - Recent batches (`?status=failed` to reprint)
+//! - GET  /api/v1/admin/print-batches/:id        - Batch with its shipments
+//! - GET  /api/v1/admin/print-batches/:id/pdf    - The merged PDF
+//! - POST /api/v1/admin/print-batches/:id/printed - Record the batch printed
+//! - POST /api/v1/admin/print-batches/:id/failed  - Record the printer's error
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::config::PrinterProfile;
+use rcommerce_core::models::{
+    CreatePrintBatchRequest, PrintBatch, PrintBatchFailedRequest, PrintBatchFilter, PrintBatchStatus,
+};
+use rcommerce_core::Error;
+
+/// Batches listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing print batches
+#[derive(Debug, Deserialize)]
+pub struct ListPrintBatchesQuery {
+    pub status: Option<PrintBatchStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/printing/profiles
+pub async fn list_profiles(State(state): State<AppState>) -> Json<Vec<PrinterProfile>> {
+    Json(state.printing.profiles().to_vec())
+}
+
+/// POST /api/v1/admin/print-batches
+pub async fn create_batch(
+    State(state): State<AppState>,
+    auth: Option<Extension<JwtAuth>>,
+    Json(request): Json<CreatePrintBatchRequest>,
+) -> Result<(StatusCode, Json<PrintBatch>), Error> {
+    let created_by = auth.map(|Extension(auth)| auth.customer_id);
+    let batch = state.printing.create_batch(request, created_by).await?;
+    Ok((StatusCode::CREATED, Json(batch)))
+}
+
+/// GET /api/v1/admin/print-batches
+pub async fn list_batches(
+    State(state): State<AppState>,
+    Query(query): Query<ListPrintBatchesQuery>,
+) -> Result<Json<Vec<PrintBatch>>, Error> {
+    let filter = PrintBatchFilter {
+        status: query.status,
+        limit: Some(query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT)),
+        offset: query.offset,
+    };
+    Ok(Json(state.printing.list(&filter).await?))
+}
+
+/// GET /api/v1/admin/print-batches/:id
+pub async fn get_batch(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PrintBatch>, Error> {
+    Ok(Json(state.printing.get(id).await?))
+}
+
+/// GET /api/v1/admin/print-batches/:id/pdf
+pub async fn get_batch_pdf(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let pdf = state.printing.pdf(id).await?;
+    let disposition = format!("inline; filename=\"print-batch-{}.pdf\"", id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        pdf,
+    ))
+}
+
+/// POST /api/v1/admin/print-batches/:id/printed
+pub async fn mark_printed(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PrintBatch>, Error> {
+    Ok(Json(state.printing.mark_printed(id).await?))
+}
+
+/// POST /api/v1/admin/print-batches/:id/failed
+pub async fn mark_failed(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PrintBatchFailedRequest>,
+) -> Result<Json<PrintBatch>, Error> {
+    Ok(Json(state.printing.mark_failed(id, &request.error).await?))
+}
+
+/// Admin router for print batches
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/printing/profiles", get(list_profiles))
+        .route("/admin/print-batches", get(list_batches).post(create_batch))
+        .route("/admin/print-batches/:id", get(get_batch))
+        .route("/admin/print-batches/:id/pdf", get(get_batch_pdf))
+        .route("/admin/print-batches/:id/printed", post(mark_printed))
+        .route("/admin/print-batches/:id/failed", post(mark_failed))
+}
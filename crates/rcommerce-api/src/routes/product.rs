@@ -1,135 +1,567 @@
 use axum::{
-    extract::{Path, State},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderName},
+    response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::middleware::conditional::last_modified;
+use crate::openapi::ErrorResponse;
 use crate::state::AppState;
+use rcommerce_core::models::{
+    is_on_sale, AssignedAttribute, AttributeFacet, Currency, Cursor, InventoryPolicy, Product, ProductFilter,
+    ProductType, WeightUnit, PRIOR_PRICE_WINDOW_DAYS,
+};
+use rcommerce_core::services::PaginationParams;
+use rcommerce_core::Error;
+use rcommerce_core::repository::{PostgresPriceHistoryRepository, PriceHistoryRepository};
+
+fn price_history(state: &AppState) -> PostgresPriceHistoryRepository {
+    PostgresPriceHistoryRepository::new(state.db.pool().clone())
+}
+
+/// Lowest price of the prior 30 days, shown next to reduced prices (EU Omnibus Directive)
+async fn lowest_prior_price(state: &AppState, product_id: Uuid, variant_id: Option<Uuid>) -> Option<Decimal> {
+    price_history(state)
+        .lowest_prior_price(product_id, variant_id, PRIOR_PRICE_WINDOW_DAYS)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get lowest prior price for {}: {}", product_id, e);
+            None
+        })
+}
+
+/// Most products listed per page
+const PER_PAGE_LIMIT: i64 = 100;
+
+/// Products read per query while streaming a listing
+const STREAM_PAGE_SIZE: i64 = 500;
+
+/// Encoded pages buffered ahead of a slow client
+const BUFFERED_PAGES: usize = 4;
+
+/// Query for `GET /products`
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProductListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// `next_cursor` of the previous page; empty for the first. Pages by
+    /// position instead of `page`, which stays fast on deep pages
+    pub cursor: Option<String>,
+    /// Category ID or slug; includes its subcategories
+    pub category: Option<String>,
+    /// Collection ID or handle; lists in the collection's sort order
+    pub collection: Option<String>,
+    pub vendor: Option<String>,
+    /// Comma-separated; products with any of the tags
+    pub tag: Option<String>,
+    /// Attribute filters, e.g. `color:red,blue;width:10..20;waterproof:true`;
+    /// products matching all of them
+    pub attr: Option<String>,
+}
+
+impl ProductListQuery {
+    fn pagination(&self) -> PaginationParams {
+        let default = PaginationParams::default();
+        PaginationParams {
+            page: self.page.unwrap_or(default.page).max(1),
+            per_page: self.per_page.unwrap_or(default.per_page).clamp(1, PER_PAGE_LIMIT),
+        }
+    }
+
+    fn tags(&self) -> Vec<String> {
+        self.tag
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// The listing filter for a query; inactive categories and unpublished
+/// collections are not found
+async fn product_filter(state: &AppState, query: &ProductListQuery) -> Result<ProductFilter, Error> {
+    let mut filter = ProductFilter {
+        vendor: query.vendor.clone(),
+        tags: query.tags(),
+        attributes: state.attributes.parse_filter(query.attr.as_deref().unwrap_or_default()).await?,
+        ..Default::default()
+    };
+    if let Some(category) = &query.category {
+        let category = state.categories.resolve_category(category).await?;
+        if !category.is_active {
+            return Err(Error::not_found("Category not found"));
+        }
+        filter.category_id = Some(category.id);
+    }
+    if let Some(collection) = &query.collection {
+        let collection = state.collections.resolve_collection(collection).await?;
+        if !collection.is_published() {
+            return Err(Error::not_found("Collection not found"));
+        }
+        filter.collection_id = Some(collection.id);
+    }
+    Ok(filter)
+}
+
+/// A product in a listing
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductSummary {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    /// Lowest price of the prior 30 days, for products on sale
+    pub lowest_price_30d: Option<Decimal>,
+    pub currency: Currency,
+    pub description: Option<String>,
+    pub vendor: Option<String>,
+    pub is_active: bool,
+    pub inventory_quantity: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pagination of a listing; cursor pages only have `per_page` and `next_cursor`
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ListMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
+    pub per_page: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<i64>,
+    /// Where the next cursor page starts; absent on the last
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Response of `GET /products`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductListResponse {
+    pub products: Vec<ProductSummary>,
+    pub meta: ListMeta,
+    /// Filterable attributes of the matching products with their values;
+    /// left out of cursor pages after the first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<AttributeFacet>,
+}
+
+/// A variant of a product
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductVariantResponse {
+    pub id: Uuid,
+    pub title: String,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub lowest_price_30d: Option<Decimal>,
+    pub inventory_quantity: i32,
+    pub attributes: Vec<AssignedAttribute>,
+}
+
+/// An image of a product, with its resized copies
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductImageResponse {
+    pub id: Uuid,
+    pub src: String,
+    pub alt_text: Option<String>,
+    pub position: i32,
+    pub variant_id: Option<Uuid>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub sizes: Vec<ImageSizeResponse>,
+}
+
+/// A resized copy of an image
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageSizeResponse {
+    /// Image size name, e.g. `thumbnail`
+    pub name: String,
+    pub src: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+/// A product with its variants and images
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductDetailResponse {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub lowest_price_30d: Option<Decimal>,
+    pub cost_price: Option<Decimal>,
+    pub currency: Currency,
+    /// For bundles, how many their components' free stock makes up
+    pub inventory_quantity: i32,
+    pub inventory_policy: InventoryPolicy,
+    pub inventory_management: bool,
+    pub weight: Option<Decimal>,
+    pub weight_unit: Option<WeightUnit>,
+    pub requires_shipping: bool,
+    pub is_active: bool,
+    pub is_featured: bool,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    pub vendor: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub attributes: Vec<AssignedAttribute>,
+    pub variants: Vec<ProductVariantResponse>,
+    pub images: Vec<ProductImageResponse>,
+}
+
+/// Response of `GET /products/:id`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductResponse {
+    pub product: ProductDetailResponse,
+}
+
+/// List products from database, optionally by category, collection,
+/// vendor or tag
+#[utoipa::path(
+    get,
+    path = "/products",
+    tag = "products",
+    params(ProductListQuery),
+    responses(
+        (status = 200, description = "A page of active products", body = ProductListResponse),
+        (status = 404, description = "Category or collection not found", body = ErrorResponse),
+    )
+)]
+pub async fn list_products(
+    State(state): State<AppState>,
+    Query(query): Query<ProductListQuery>,
+) -> Result<Json<ProductListResponse>, Error> {
+    let filter = product_filter(&state, &query).await?;
+    let pagination = query.pagination();
+    if query.cursor.is_some() {
+        let cursor = Cursor::parse(query.cursor.as_deref())?;
+        let facets = match cursor {
+            Some(_) => Vec::new(),
+            None => attribute_facets(&state, &filter).await,
+        };
+        let page = state
+            .product_service
+            .list_products_after(Some(filter), cursor.as_ref(), pagination.per_page)
+            .await?;
+        return Ok(Json(ProductListResponse {
+            products: product_summaries(&state, page.items).await,
+            meta: ListMeta {
+                per_page: pagination.per_page,
+                next_cursor: page.next_cursor,
+                ..Default::default()
+            },
+            facets,
+        }));
+    }
+
+    let facets = attribute_facets(&state, &filter).await;
 
-/// List products from database
-pub async fn list_products(State(state): State<AppState>) -> Json<serde_json::Value> {
-    match state
+    Ok(match state
         .product_service
-        .list_products(None, rcommerce_core::services::PaginationParams::default())
+        .list_products(Some(filter), pagination)
         .await
     {
-        Ok(product_list) => {
-            let products: Vec<serde_json::Value> = product_list
-                .products
-                .into_iter()
-                .map(|p| {
-                    serde_json::json!({
-                        "id": p.id,
-                        "title": p.title,
-                        "slug": p.slug,
-                        "price": p.price,
-                        "currency": p.currency,
-                        "description": p.description,
-                        "is_active": p.is_active,
-                        "inventory_quantity": p.inventory_quantity,
-                        "created_at": p.created_at
-                    })
-                })
-                .collect();
-
-            Json(serde_json::json!({
-                "products": products,
-                "meta": {
-                    "total": product_list.pagination.total,
-                    "page": product_list.pagination.page,
-                    "per_page": product_list.pagination.per_page,
-                    "total_pages": product_list.pagination.total_pages
-                }
-            }))
-        }
+        Ok(product_list) => Json(ProductListResponse {
+            products: product_summaries(&state, product_list.products).await,
+            meta: ListMeta {
+                total: Some(product_list.pagination.total),
+                page: Some(product_list.pagination.page),
+                per_page: product_list.pagination.per_page,
+                total_pages: Some(product_list.pagination.total_pages),
+                next_cursor: None,
+            },
+            facets,
+        }),
         Err(e) => {
             tracing::error!("Failed to list products: {}", e);
             // Return empty list on error for now
-            Json(serde_json::json!({
-                "products": [],
-                "meta": {
-                    "total": 0,
-                    "page": 1,
-                    "per_page": 20,
-                    "total_pages": 0
-                }
-            }))
+            Json(ProductListResponse {
+                products: Vec::new(),
+                meta: ListMeta {
+                    total: Some(0),
+                    page: Some(1),
+                    per_page: 20,
+                    total_pages: Some(0),
+                    next_cursor: None,
+                },
+                facets: Vec::new(),
+            })
         }
-    }
+    })
+}
+
+/// Facets of a listing; a listing without them beats a failed listing
+async fn attribute_facets(state: &AppState, filter: &ProductFilter) -> Vec<AttributeFacet> {
+    let facets = async {
+        let attributes = state.attributes.filterable().await?;
+        if attributes.is_empty() {
+            return Ok(Vec::new());
+        }
+        state.product_service.attribute_facets(filter, &attributes).await
+    };
+    facets.await.unwrap_or_else(|e: Error| {
+        tracing::warn!("Failed to count attribute facets: {}", e);
+        Vec::new()
+    })
+}
+
+/// Listing entries, with the lowest prior price of products on sale
+async fn product_summaries(state: &AppState, products: Vec<Product>) -> Vec<ProductSummary> {
+    let on_sale: Vec<Uuid> = products
+        .iter()
+        .filter(|p| is_on_sale(p.price, p.compare_at_price))
+        .map(|p| p.id)
+        .collect();
+    let lowest_prices = price_history(state)
+        .lowest_prior_prices(&on_sale, PRIOR_PRICE_WINDOW_DAYS)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get lowest prior prices: {}", e);
+            Default::default()
+        });
+
+    products
+        .into_iter()
+        .map(|p| ProductSummary {
+            lowest_price_30d: lowest_prices.get(&p.id).copied(),
+            id: p.id,
+            title: p.title,
+            slug: p.slug,
+            price: p.price,
+            compare_at_price: p.compare_at_price,
+            currency: p.currency,
+            description: p.description,
+            vendor: p.vendor,
+            is_active: p.is_active,
+            inventory_quantity: p.inventory_quantity,
+            created_at: p.created_at,
+        })
+        .collect()
 }
 
 /// Get product by ID from database
+#[utoipa::path(
+    get,
+    path = "/products/{id}",
+    tag = "products",
+    params(("id" = Uuid, Path, description = "Product ID")),
+    responses(
+        (status = 200, description = "The product with its variants and images", body = ProductResponse),
+        (status = 400, description = "Invalid product ID", body = ErrorResponse),
+        (status = 404, description = "Product not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_product(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Json<serde_json::Value> {
-    let product_id = match Uuid::parse_str(&id) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return Json(serde_json::json!({
-                "error": "Invalid product ID format"
-            }));
-        }
+) -> Result<([(HeaderName, String); 1], Json<ProductResponse>), Error> {
+    let product_id = Uuid::parse_str(&id).map_err(|_| Error::validation("Invalid product ID format"))?;
+
+    let product_detail = state
+        .product_service
+        .get_product(product_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get product: {}", e);
+            Error::Other("Failed to retrieve product".to_string())
+        })?
+        .ok_or_else(|| Error::not_found("Product not found"))?;
+
+    // Variant and image edits don't always touch the product row
+    let updated_at = product_detail
+        .variants
+        .iter()
+        .map(|v| v.updated_at)
+        .chain(product_detail.images.iter().map(|i| i.updated_at))
+        .fold(product_detail.product.updated_at, std::cmp::max);
+    let mut attributes = state.attributes.product_attributes(product_id).await?;
+    let p = product_detail.product;
+    let inventory_quantity = match p.product_type {
+        ProductType::Bundle => state
+            .bundle_service
+            .available_quantity(p.id)
+            .await?
+            .unwrap_or(p.inventory_quantity),
+        _ => p.inventory_quantity,
+    };
+    let lowest_price_30d = if is_on_sale(p.price, p.compare_at_price) {
+        lowest_prior_price(&state, p.id, None).await
+    } else {
+        None
     };
+    let mut variants = Vec::with_capacity(product_detail.variants.len());
+    for v in product_detail.variants {
+        let lowest_price_30d = if is_on_sale(v.price, v.compare_at_price) {
+            lowest_prior_price(&state, p.id, Some(v.id)).await
+        } else {
+            None
+        };
+        variants.push(ProductVariantResponse {
+            id: v.id,
+            title: v.title,
+            sku: v.sku,
+            barcode: v.barcode,
+            price: v.price,
+            compare_at_price: v.compare_at_price,
+            lowest_price_30d,
+            inventory_quantity: v.inventory_quantity,
+            attributes: attributes.variant(v.id),
+        });
+    }
+    let images = product_detail
+        .images
+        .into_iter()
+        .map(|i| ProductImageResponse {
+            sizes: i
+                .variants
+                .iter()
+                .map(|v| ImageSizeResponse {
+                    name: v.name.clone(),
+                    src: v.src.clone(),
+                    width: v.width,
+                    height: v.height,
+                })
+                .collect(),
+            id: i.id,
+            src: i.src,
+            alt_text: i.alt_text,
+            position: i.position,
+            variant_id: i.variant_id,
+            width: i.width,
+            height: i.height,
+        })
+        .collect();
 
-    match state.product_service.get_product(product_id).await {
-        Ok(Some(product_detail)) => {
-            let p = product_detail.product;
-            Json(serde_json::json!({
-                "product": {
-                    "id": p.id,
-                    "title": p.title,
-                    "slug": p.slug,
-                    "description": p.description,
-                    "price": p.price,
-                    "compare_at_price": p.compare_at_price,
-                    "cost_price": p.cost_price,
-                    "currency": p.currency,
-                    "inventory_quantity": p.inventory_quantity,
-                    "inventory_policy": p.inventory_policy,
-                    "inventory_management": p.inventory_management,
-                    "weight": p.weight,
-                    "weight_unit": p.weight_unit,
-                    "requires_shipping": p.requires_shipping,
-                    "is_active": p.is_active,
-                    "is_featured": p.is_featured,
-                    "seo_title": p.seo_title,
-                    "seo_description": p.seo_description,
-                    "created_at": p.created_at,
-                    "updated_at": p.updated_at,
-                    "published_at": p.published_at,
-                    "variants": product_detail.variants.into_iter().map(|v| serde_json::json!({
-                        "id": v.id,
-                        "title": v.title,
-                        "sku": v.sku,
-                        "price": v.price,
-                        "inventory_quantity": v.inventory_quantity
-                    })).collect::<Vec<_>>(),
-                    "images": product_detail.images.into_iter().map(|i| serde_json::json!({
-                        "id": i.id,
-                        "src": i.src,
-                        "alt_text": i.alt_text
-                    })).collect::<Vec<_>>()
+    Ok((last_modified(updated_at), Json(ProductResponse {
+        product: ProductDetailResponse {
+            id: p.id,
+            title: p.title,
+            slug: p.slug,
+            description: p.description,
+            price: p.price,
+            compare_at_price: p.compare_at_price,
+            lowest_price_30d,
+            cost_price: p.cost_price,
+            currency: p.currency,
+            inventory_quantity,
+            inventory_policy: p.inventory_policy,
+            inventory_management: p.inventory_management,
+            weight: p.weight,
+            weight_unit: p.weight_unit,
+            requires_shipping: p.requires_shipping,
+            is_active: p.is_active,
+            is_featured: p.is_featured,
+            seo_title: p.seo_title,
+            seo_description: p.seo_description,
+            vendor: p.vendor,
+            created_at: p.created_at,
+            updated_at: p.updated_at,
+            published_at: p.published_at,
+            attributes: std::mem::take(&mut attributes.product),
+            variants,
+            images,
+        },
+    })))
+}
+
+/// GET /api/v1/products/stream
+///
+/// Every product matching the listing filters (`page` is ignored) as one
+/// JSON array, newest first. Products are read and sent `per_page` at a
+/// time (at most `STREAM_PAGE_SIZE`), so a large catalog is never held in
+/// memory. Collection sort orders aren't supported.
+///
+/// `GET /products` itself stays buffered: its pages are capped at
+/// `PER_PAGE_LIMIT` products and carry totals and facets, so only the
+/// whole-catalog fetch needs streaming.
+pub async fn stream_products(
+    State(state): State<AppState>,
+    Query(query): Query<ProductListQuery>,
+) -> Result<Response, Error> {
+    let filter = product_filter(&state, &query).await?;
+    let page_size = query.per_page.unwrap_or(STREAM_PAGE_SIZE).clamp(1, STREAM_PAGE_SIZE);
+    // Read the first page here, so a bad filter is still an error response
+    let first = state
+        .product_service
+        .list_products_after(Some(filter.clone()), None, page_size)
+        .await?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(BUFFERED_PAGES);
+    tokio::spawn(async move {
+        let mut page = first;
+        let mut chunk = b"[".to_vec();
+        let mut written = 0_u64;
+        loop {
+            let next = page
+                .next_cursor
+                .as_ref()
+                .and_then(|_| page.items.last())
+                .map(|p| Cursor::new(p.created_at, p.id));
+            for summary in product_summaries(&state, page.items).await {
+                if written > 0 {
+                    chunk.push(b',');
                 }
-            }))
-        }
-        Ok(None) => Json(serde_json::json!({
-            "error": "Product not found"
-        })),
-        Err(e) => {
-            tracing::error!("Failed to get product: {}", e);
-            Json(serde_json::json!({
-                "error": "Failed to retrieve product"
-            }))
+                if let Err(e) = serde_json::to_writer(&mut chunk, &summary) {
+                    let _ = tx.send(Err(Error::Other(format!("Failed to encode product: {}", e)))).await;
+                    return;
+                }
+                written += 1;
+            }
+            let Some(cursor) = next else {
+                chunk.push(b']');
+                let _ = tx.send(Ok(chunk)).await;
+                return;
+            };
+            if tx.send(Ok(std::mem::take(&mut chunk))).await.is_err() {
+                return;
+            }
+            page = match state
+                .product_service
+                .list_products_after(Some(filter.clone()), Some(&cursor), page_size)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => {
+                    tracing::error!("Product stream failed after {} products: {}", written, e);
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
         }
-    }
+    });
+
+    let body = Body::from_stream(
+        ReceiverStream::new(rx).map(|chunk| chunk.map_err(std::io::Error::other)),
+    );
+    Ok(([(header::CONTENT_TYPE, "application/json")], body).into_response())
 }
 
 /// Router for product routes
 /// 
 /// Public routes:
-/// - GET /products - List products (public read)
+/// - GET /products - List products (public read), filtered by `category`,
+///   `collection`, `vendor`, `tag` or `attr`, with attribute facets
+/// - GET /products/stream - Every matching product as one streamed array
 /// - GET /products/:id - Get product details (public read)
 /// 
 /// Protected routes (require products:write scope):
@@ -139,5 +571,6 @@ pub async fn get_product(
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/products", get(list_products))
+        .route("/products/stream", get(stream_products))
         .route("/products/:id", get(get_product))
 }
@@ -0,0 +1,172 @@
+//! Purchase Limit API Routes
+//!
+//! Rules and allocation lists are checked when items are added to a cart
+//! and at checkout. A refused purchase is a 400 whose message carries
+//! `{"errors": [{"field": <product id>, "message", "code"}]}` with one of
+//! the codes `purchase_limit_exceeded`, `household_limit_exceeded`,
+//! `not_yet_available` or `early_access_only`.
+//! - GET    /api/v1/admin/purchase-limits                       - Rules (`?product_id=`)
+//! - POST   /api/v1/admin/purchase-limits                       - Define a rule
+//! - GET    /api/v1/admin/purchase-limits/:id                   - Get a rule
+//! - PUT    /api/v1/admin/purchase-limits/:id                   - Rename, change or switch a rule on/off
+//! - DELETE /api/v1/admin/purchase-limits/:id                   - Delete a rule
+//! - GET    /api/v1/admin/allocation-lists                      - Allocation lists (`?product_id=`)
+//! - POST   /api/v1/admin/allocation-lists                      - Define a list
+//! - GET    /api/v1/admin/allocation-lists/:id                  - List with its member count
+//! - DELETE /api/v1/admin/allocation-lists/:id                  - Delete a list
+//! - GET    /api/v1/admin/allocation-lists/:id/members          - Members (`?limit=&offset=`)
+//! - POST   /api/v1/admin/allocation-lists/:id/members          - Add customers by id or email
+//! - DELETE /api/v1/admin/allocation-lists/:id/members/:customer_id - Remove a customer
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AddAllocationMembersRequest, AllocationList, AllocationListDetails, AllocationListMember,
+    CreateAllocationListRequest, CreatePurchaseLimitRuleRequest, PurchaseLimitRule, UpdatePurchaseLimitRuleRequest,
+};
+use rcommerce_core::Error;
+
+/// Members listed at most per page
+const MEMBERS_LIMIT: i64 = 500;
+
+/// Query parameters for listing rules and lists
+#[derive(Debug, Deserialize)]
+pub struct ProductQuery {
+    pub product_id: Option<Uuid>,
+}
+
+/// Query parameters for listing members
+#[derive(Debug, Deserialize)]
+pub struct MembersQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/purchase-limits
+pub async fn list_rules(
+    State(state): State<AppState>,
+    Query(query): Query<ProductQuery>,
+) -> Result<Json<Vec<PurchaseLimitRule>>, Error> {
+    Ok(Json(state.purchase_limits.list_rules(query.product_id).await?))
+}
+
+/// POST /api/v1/admin/purchase-limits
+pub async fn create_rule(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreatePurchaseLimitRuleRequest>,
+) -> Result<(StatusCode, Json<PurchaseLimitRule>), Error> {
+    let rule = state.purchase_limits.create_rule(request, Some(auth.customer_id)).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// GET /api/v1/admin/purchase-limits/:id
+pub async fn get_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseLimitRule>, Error> {
+    Ok(Json(state.purchase_limits.get_rule(id).await?))
+}
+
+/// PUT /api/v1/admin/purchase-limits/:id
+pub async fn update_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePurchaseLimitRuleRequest>,
+) -> Result<Json<PurchaseLimitRule>, Error> {
+    Ok(Json(state.purchase_limits.update_rule(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/purchase-limits/:id
+pub async fn delete_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.purchase_limits.delete_rule(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/allocation-lists
+pub async fn list_lists(
+    State(state): State<AppState>,
+    Query(query): Query<ProductQuery>,
+) -> Result<Json<Vec<AllocationList>>, Error> {
+    Ok(Json(state.purchase_limits.list_lists(query.product_id).await?))
+}
+
+/// POST /api/v1/admin/allocation-lists
+pub async fn create_list(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateAllocationListRequest>,
+) -> Result<(StatusCode, Json<AllocationList>), Error> {
+    let list = state.purchase_limits.create_list(request, Some(auth.customer_id)).await?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+/// GET /api/v1/admin/allocation-lists/:id
+pub async fn get_list(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AllocationListDetails>, Error> {
+    Ok(Json(state.purchase_limits.get_list(id).await?))
+}
+
+/// DELETE /api/v1/admin/allocation-lists/:id
+pub async fn delete_list(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.purchase_limits.delete_list(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/allocation-lists/:id/members
+pub async fn list_members(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<MembersQuery>,
+) -> Result<Json<Vec<AllocationListMember>>, Error> {
+    let limit = query.limit.unwrap_or(MEMBERS_LIMIT).clamp(1, MEMBERS_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    Ok(Json(state.purchase_limits.members(id, limit, offset).await?))
+}
+
+/// POST /api/v1/admin/allocation-lists/:id/members
+pub async fn add_members(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AddAllocationMembersRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let added = state.purchase_limits.add_members(id, request).await?;
+    Ok(Json(serde_json::json!({ "added": added })))
+}
+
+/// DELETE /api/v1/admin/allocation-lists/:id/members/:customer_id
+pub async fn remove_member(
+    State(state): State<AppState>,
+    Path((id, customer_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    state.purchase_limits.remove_member(id, customer_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for purchase limit admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/purchase-limits", get(list_rules).post(create_rule))
+        .route("/admin/purchase-limits/:id", get(get_rule).put(update_rule).delete(delete_rule))
+        .route("/admin/allocation-lists", get(list_lists).post(create_list))
+        .route("/admin/allocation-lists/:id", get(get_list).delete(delete_list))
+        .route("/admin/allocation-lists/:id/members", get(list_members).post(add_members))
+        .route("/admin/allocation-lists/:id/members/:customer_id", delete(remove_member))
+}
@@ -0,0 +1,245 @@
+//! Purchasing API Routes
+//!
+//! Suppliers and purchase orders. Placing an order adds its quantities to
+//! incoming stock at its location; receiving moves delivered units to
+//! available stock (`[purchasing]` for low stock alerts and drafts):
+//! - GET    /api/v1/admin/suppliers                           - Suppliers (`?include_inactive=true`)
+//! - POST   /api/v1/admin/suppliers                           - Create a supplier
+//! - GET    /api/v1/admin/suppliers/:id                       - Get a supplier
+//! - PUT    /api/v1/admin/suppliers/:id                       - Update a supplier
+//! - GET    /api/v1/admin/suppliers/:id/products              - Products the supplier sells
+//! - PUT    /api/v1/admin/suppliers/:id/products              - Add a product, or change its cost, SKU or preference
+//! - DELETE /api/v1/admin/suppliers/:id/products/:product_id  - Remove a product from the supplier
+//! - GET    /api/v1/admin/purchase-orders                     - Purchase orders (`?status=ordered&supplier_id=...`)
+//! - POST   /api/v1/admin/purchase-orders                     - Draft a purchase order
+//! - GET    /api/v1/admin/purchase-orders/:id                 - Get a purchase order with its lines
+//! - PUT    /api/v1/admin/purchase-orders/:id                 - Change the expected date and notes, or a draft's lines
+//! - POST   /api/v1/admin/purchase-orders/:id/place           - Place a draft and send it to the supplier
+//! - POST   /api/v1/admin/purchase-orders/:id/receive         - Receive a delivery, flagging invoice discrepancies (then ship backorders it restocked)
+//! - POST   /api/v1/admin/purchase-orders/:id/cancel          - Cancel a purchase order
+//! - GET    /api/v1/admin/purchase-orders/low-stock           - Levels at or below their reorder point
+//! - POST   /api/v1/admin/purchase-orders/reorder             - Run the low stock check now
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::inventory::purchasing::MAX_LIST_LIMIT;
+use rcommerce_core::inventory::{
+    CreatePurchaseOrderRequest, CreateSupplierRequest, LowStockLevel, PurchaseOrder, PurchaseOrderDetail,
+    PurchaseOrderStatus, ReceivePurchaseOrderRequest, ReorderReport, Supplier, SupplierProduct,
+    SupplierProductRequest, UpdatePurchaseOrderRequest, UpdateSupplierRequest,
+};
+use rcommerce_core::repository::PurchaseOrderRepository;
+use rcommerce_core::Error;
+
+/// Query parameters for listing suppliers
+#[derive(Debug, Deserialize)]
+pub struct ListSuppliersQuery {
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+/// Query parameters for listing purchase orders
+#[derive(Debug, Deserialize)]
+pub struct ListPurchaseOrdersQuery {
+    pub status: Option<PurchaseOrderStatus>,
+    pub supplier_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/suppliers
+pub async fn list_suppliers(
+    State(state): State<AppState>,
+    Query(query): Query<ListSuppliersQuery>,
+) -> Result<Json<Vec<Supplier>>, Error> {
+    Ok(Json(state.purchasing.repository().list_suppliers(query.include_inactive).await?))
+}
+
+/// POST /api/v1/admin/suppliers
+pub async fn create_supplier(
+    State(state): State<AppState>,
+    Json(request): Json<CreateSupplierRequest>,
+) -> Result<(StatusCode, Json<Supplier>), Error> {
+    let supplier = state.purchasing.create_supplier(request).await?;
+    Ok((StatusCode::CREATED, Json(supplier)))
+}
+
+/// GET /api/v1/admin/suppliers/:id
+pub async fn get_supplier(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Supplier>, Error> {
+    Ok(Json(state.purchasing.get_supplier(id).await?))
+}
+
+/// PUT /api/v1/admin/suppliers/:id
+pub async fn update_supplier(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateSupplierRequest>,
+) -> Result<Json<Supplier>, Error> {
+    Ok(Json(state.purchasing.update_supplier(id, request).await?))
+}
+
+/// GET /api/v1/admin/suppliers/:id/products
+pub async fn list_supplier_products(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<SupplierProduct>>, Error> {
+    state.purchasing.get_supplier(id).await?;
+    Ok(Json(state.purchasing.repository().list_supplier_products(id).await?))
+}
+
+/// PUT /api/v1/admin/suppliers/:id/products
+pub async fn set_supplier_product(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SupplierProductRequest>,
+) -> Result<Json<SupplierProduct>, Error> {
+    Ok(Json(state.purchasing.set_supplier_product(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/suppliers/:id/products/:product_id
+pub async fn remove_supplier_product(
+    State(state): State<AppState>,
+    Path((id, product_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    if !state.purchasing.repository().remove_supplier_product(id, product_id).await? {
+        return Err(Error::not_found("Supplier product not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/purchase-orders
+pub async fn list_purchase_orders(
+    State(state): State<AppState>,
+    Query(query): Query<ListPurchaseOrdersQuery>,
+) -> Result<Json<Vec<PurchaseOrder>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_LIST_LIMIT);
+    let purchase_orders = state
+        .purchasing
+        .repository()
+        .list_purchase_orders(query.status, query.supplier_id, limit)
+        .await?;
+    Ok(Json(purchase_orders))
+}
+
+/// POST /api/v1/admin/purchase-orders
+pub async fn create_purchase_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreatePurchaseOrderRequest>,
+) -> Result<(StatusCode, Json<PurchaseOrderDetail>), Error> {
+    let purchase_order = state.purchasing.create_purchase_order(Some(auth.customer_id), request).await?;
+    Ok((StatusCode::CREATED, Json(purchase_order)))
+}
+
+/// GET /api/v1/admin/purchase-orders/:id
+pub async fn get_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    Ok(Json(state.purchasing.get_purchase_order(id).await?))
+}
+
+/// PUT /api/v1/admin/purchase-orders/:id
+pub async fn update_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePurchaseOrderRequest>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    Ok(Json(state.purchasing.update_purchase_order(id, request).await?))
+}
+
+/// POST /api/v1/admin/purchase-orders/:id/place
+pub async fn place_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    let detail = state.purchasing.place(id).await?;
+
+    // Send it to the supplier now rather than on the next po_dispatch run
+    let po_dispatch = state.po_dispatch.clone();
+    tokio::spawn(async move {
+        if let Err(e) = po_dispatch.dispatch(id).await {
+            tracing::warn!("Failed to dispatch purchase order {}, the po_dispatch job will retry: {}", id, e);
+        }
+    });
+    Ok(Json(detail))
+}
+
+/// POST /api/v1/admin/purchase-orders/:id/receive
+pub async fn receive_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReceivePurchaseOrderRequest>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    let received = request.items.clone();
+    let detail = state.purchasing.receive(id, request).await?;
+
+    // Flag invoiced quantities and costs that differ from the delivery and the order
+    if let Err(e) = state.po_dispatch.match_receipt(id, &received).await {
+        tracing::warn!("Failed to match receipt of purchase order {}: {}", id, e);
+    }
+
+    // Ship backorders the delivery restocked, without holding up the response
+    let shipments = state.shipments.clone();
+    tokio::spawn(async move {
+        if let Err(e) = shipments.release_backorders().await {
+            tracing::warn!("Failed to ship backorders after receiving purchase order: {}", e);
+        }
+    });
+    Ok(Json(detail))
+}
+
+/// POST /api/v1/admin/purchase-orders/:id/cancel
+pub async fn cancel_purchase_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PurchaseOrderDetail>, Error> {
+    Ok(Json(state.purchasing.cancel(id).await?))
+}
+
+/// GET /api/v1/admin/purchase-orders/low-stock
+pub async fn list_low_stock(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<LowStockLevel>>, Error> {
+    Ok(Json(state.purchasing.repository().low_stock_levels().await?))
+}
+
+/// POST /api/v1/admin/purchase-orders/reorder
+pub async fn run_reorder(
+    State(state): State<AppState>,
+) -> Result<Json<ReorderReport>, Error> {
+    Ok(Json(state.purchasing.check_reorders().await?))
+}
+
+/// Admin router for suppliers and purchase orders
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/suppliers", get(list_suppliers).post(create_supplier))
+        .route("/admin/suppliers/:id", get(get_supplier).put(update_supplier))
+        .route(
+            "/admin/suppliers/:id/products",
+            get(list_supplier_products).put(set_supplier_product),
+        )
+        .route("/admin/suppliers/:id/products/:product_id", delete(remove_supplier_product))
+        .route("/admin/purchase-orders", get(list_purchase_orders).post(create_purchase_order))
+        .route("/admin/purchase-orders/low-stock", get(list_low_stock))
+        .route("/admin/purchase-orders/reorder", post(run_reorder))
+        .route(
+            "/admin/purchase-orders/:id",
+            get(get_purchase_order).put(update_purchase_order),
+        )
+        .route("/admin/purchase-orders/:id/place", post(place_purchase_order))
+        .route("/admin/purchase-orders/:id/receive", post(receive_purchase_order))
+        .route("/admin/purchase-orders/:id/cancel", post(cancel_purchase_order))
+}
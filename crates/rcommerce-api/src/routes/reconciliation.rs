@@ -0,0 +1,89 @@
+//! Payout Reconciliation API Routes
+//!
+//! Gateway payouts are imported by the `payout_reconciliation` job (with
+//! `[payment.reconciliation] enabled = true`) and each paid out charge and
+//! refund is matched against the recorded payment or refund. Report ranges
+//! are UTC days of payout arrival, both inclusive (default the last 30 days):
+//! - GET  /api/v1/admin/payments/reconciliation  - Totals, flagged transactions and unsettled payments (`?from=&to=&gateway=`)
+//! - GET  /api/v1/admin/payments/payouts         - Imported payouts, latest first (`?gateway=`)
+//! - GET  /api/v1/admin/payments/payouts/:id     - A payout with its matched transactions
+//! - POST /api/v1/admin/payments/payouts/import  - Import and match payouts now
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{Payout, PayoutDetail, PayoutImport, ReconciliationReport};
+use rcommerce_core::reports::SalesRange;
+use rcommerce_core::Error;
+
+/// Days covered when the request names no range
+const DEFAULT_DAYS: i64 = 30;
+
+/// Payouts listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters of the reconciliation report
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub gateway: Option<String>,
+}
+
+/// Query parameters of the payout list
+#[derive(Debug, Deserialize)]
+pub struct PayoutListQuery {
+    pub gateway: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// GET /api/v1/admin/payments/reconciliation
+pub async fn reconciliation_report(
+    State(state): State<AppState>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<ReconciliationReport>, Error> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_DAYS - 1));
+    let range = SalesRange::new(from, to)?;
+    Ok(Json(state.payouts.report(range, query.gateway.as_deref()).await?))
+}
+
+/// GET /api/v1/admin/payments/payouts
+pub async fn list_payouts(
+    State(state): State<AppState>,
+    Query(query): Query<PayoutListQuery>,
+) -> Result<Json<Vec<Payout>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    Ok(Json(state.payouts.payouts(query.gateway.as_deref(), limit, offset).await?))
+}
+
+/// GET /api/v1/admin/payments/payouts/:id
+pub async fn get_payout(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PayoutDetail>, Error> {
+    Ok(Json(state.payouts.payout(id).await?))
+}
+
+/// POST /api/v1/admin/payments/payouts/import
+pub async fn import_payouts(State(state): State<AppState>) -> Result<Json<PayoutImport>, Error> {
+    Ok(Json(state.payouts.import().await?))
+}
+
+/// Router for payout reconciliation (mounted under the admin routes)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/payments/reconciliation", get(reconciliation_report))
+        .route("/admin/payments/payouts", get(list_payouts))
+        .route("/admin/payments/payouts/import", post(import_payouts))
+        .route("/admin/payments/payouts/:id", get(get_payout))
+}
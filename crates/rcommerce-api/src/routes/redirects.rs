@@ -0,0 +1,205 @@
+//! Redirect API Routes
+//!
+//! Old product and category slugs keep working: they resolve to the current
+//! slug (`[redirects]` paths), and product lookups by an old slug answer
+//! with a 301 to the current one. Manual redirects cover other paths, by
+//! exact path or by prefix (a source ending in `*`):
+//! - GET    /api/v1/redirects/resolve                       - Where a storefront path moved (`?path=`)
+//! - GET    /api/v1/products/by-slug/:slug                  - Get product by slug (301 for an old slug)
+//! - GET    /api/v1/storefront/redirects/resolve            - Same, with a publishable key
+//! - GET    /api/v1/storefront/products/by-slug/:slug       - Same, with a publishable key
+//! - GET    /api/v1/admin/redirects                         - Manual redirects (`?limit=&offset=`)
+//! - POST   /api/v1/admin/redirects                         - Create a redirect
+//! - POST   /api/v1/admin/redirects/import                  - Import redirects from CSV (`?overwrite=true`)
+//! - GET    /api/v1/admin/redirects/:id                     - Get a redirect
+//! - PUT    /api/v1/admin/redirects/:id                     - Change a redirect's target or status
+//! - DELETE /api/v1/admin/redirects/:id                     - Delete a redirect
+//! - GET    /api/v1/admin/products/:id/slug-history         - A product's old slugs
+//! - GET    /api/v1/admin/categories/:id/slug-history       - A category's old slugs
+
+use axum::{
+    extract::{DefaultBodyLimit, Extension, OriginalUri, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::routes::product;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreateUrlRedirectRequest, RedirectImportResult, RedirectResolution, SlugEntityType, SlugHistoryEntry, UpdateUrlRedirectRequest,
+    UrlRedirect,
+};
+use rcommerce_core::Error;
+
+/// Redirects listed at most per page
+const LIST_LIMIT: i64 = 500;
+
+/// Largest redirect CSV accepted
+const IMPORT_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Query parameters for resolving a path
+#[derive(Debug, Deserialize)]
+pub struct ResolveQuery {
+    pub path: String,
+}
+
+/// Query parameters for listing redirects
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Query parameters for importing redirects
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    /// Replace the target and status of sources that already redirect
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// GET /api/v1/redirects/resolve
+pub async fn resolve(
+    State(state): State<AppState>,
+    Query(query): Query<ResolveQuery>,
+) -> Result<Json<RedirectResolution>, Error> {
+    state
+        .redirects
+        .resolve(&query.path)
+        .await?
+        .map(Json)
+        .ok_or_else(|| Error::not_found("No redirect for this path"))
+}
+
+/// GET /api/v1/products/by-slug/:slug
+pub async fn get_product_by_slug(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    Path(slug): Path<String>,
+) -> Result<Response, Error> {
+    let lookup = state
+        .redirects
+        .lookup_slug(SlugEntityType::Product, &slug)
+        .await?
+        .ok_or_else(|| Error::not_found("Product not found"))?;
+
+    if lookup.slug != slug {
+        // Same route with the current slug, keeping the query (e.g. `?key=`)
+        let base = uri.path().rsplit_once('/').map(|(base, _)| base).unwrap_or_default();
+        let mut location = format!("{}/{}", base, lookup.slug);
+        if let Some(query) = uri.query() {
+            location.push('?');
+            location.push_str(query);
+        }
+        return Ok((StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response());
+    }
+
+    Ok(product::get_product(State(state), Path(lookup.entity_id.to_string()))
+        .await
+        .into_response())
+}
+
+/// GET /api/v1/admin/redirects
+pub async fn list_redirects(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<UrlRedirect>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+    Ok(Json(state.redirects.list(limit, offset).await?))
+}
+
+/// POST /api/v1/admin/redirects
+pub async fn create_redirect(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateUrlRedirectRequest>,
+) -> Result<(StatusCode, Json<UrlRedirect>), Error> {
+    let redirect = state.redirects.create(request, Some(auth.customer_id)).await?;
+    Ok((StatusCode::CREATED, Json(redirect)))
+}
+
+/// POST /api/v1/admin/redirects/import
+pub async fn import_redirects(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<RedirectImportResult>, Error> {
+    Ok(Json(
+        state
+            .redirects
+            .import_csv(&body, query.overwrite, Some(auth.customer_id))
+            .await?,
+    ))
+}
+
+/// GET /api/v1/admin/redirects/:id
+pub async fn get_redirect(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UrlRedirect>, Error> {
+    Ok(Json(state.redirects.get(id).await?))
+}
+
+/// PUT /api/v1/admin/redirects/:id
+pub async fn update_redirect(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateUrlRedirectRequest>,
+) -> Result<Json<UrlRedirect>, Error> {
+    Ok(Json(state.redirects.update(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/redirects/:id
+pub async fn delete_redirect(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.redirects.delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/products/:id/slug-history
+pub async fn product_slug_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<SlugHistoryEntry>>, Error> {
+    Ok(Json(state.redirects.slug_history(SlugEntityType::Product, id).await?))
+}
+
+/// GET /api/v1/admin/categories/:id/slug-history
+pub async fn category_slug_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<SlugHistoryEntry>>, Error> {
+    Ok(Json(state.redirects.slug_history(SlugEntityType::Category, id).await?))
+}
+
+/// Router for redirect resolution
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/redirects/resolve", get(resolve))
+        .route("/products/by-slug/:slug", get(get_product_by_slug))
+}
+
+/// Admin router for manual redirects and slug history
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/redirects", get(list_redirects).post(create_redirect))
+        .route(
+            "/admin/redirects/import",
+            post(import_redirects).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route(
+            "/admin/redirects/:id",
+            get(get_redirect).put(update_redirect).delete(delete_redirect),
+        )
+        .route("/admin/products/:id/slug-history", get(product_slug_history))
+        .route("/admin/categories/:id/slug-history", get(category_slug_history))
+}
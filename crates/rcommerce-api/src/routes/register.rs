@@ -0,0 +1,158 @@
+//! POS Register API Routes
+//!
+//! Cash drawer sessions for the point-of-sale channel (staff only):
+//! - GET  /api/v1/pos/registers                    - List registers
+//! - POST /api/v1/pos/registers                    - Create a register
+//! - GET  /api/v1/pos/registers/:id/sessions       - Recent sessions of a register
+//! - POST /api/v1/pos/registers/:id/sessions       - Open a session with a cash float
+//! - GET  /api/v1/pos/sessions/:id                 - Session with running totals by tender
+//! - GET  /api/v1/pos/sessions/:id/transactions    - Transactions recorded in a session
+//! - POST /api/v1/pos/sessions/:id/transactions    - Record a sale, refund, paid in or paid out
+//! - POST /api/v1/pos/sessions/:id/close           - Close with the counted cash (returns the Z-report)
+//! - GET  /api/v1/pos/sessions/:id/z-report        - Z-report of a closed session (`?format=csv` to download)
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CloseRegisterSessionRequest, CreateRegisterRequest, OpenRegisterSessionRequest, PosRegister,
+    RecordRegisterTransactionRequest, RegisterSession, RegisterTransaction, ZReport,
+};
+use rcommerce_core::Error;
+
+/// Query parameters for the Z-report export
+#[derive(Debug, Default, Deserialize)]
+pub struct ZReportQuery {
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+}
+
+/// GET /api/v1/pos/registers
+pub async fn list_registers(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<Vec<PosRegister>>, Error> {
+    Ok(Json(state.registers.list_registers(auth.customer_id).await?))
+}
+
+/// POST /api/v1/pos/registers
+pub async fn create_register(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateRegisterRequest>,
+) -> Result<(StatusCode, Json<PosRegister>), Error> {
+    let register = state.registers.create_register(auth.customer_id, request).await?;
+    Ok((StatusCode::CREATED, Json(register)))
+}
+
+/// GET /api/v1/pos/registers/:id/sessions
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(register_id): Path<Uuid>,
+) -> Result<Json<Vec<RegisterSession>>, Error> {
+    Ok(Json(state.registers.list_sessions(auth.customer_id, register_id).await?))
+}
+
+/// POST /api/v1/pos/registers/:id/sessions
+pub async fn open_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(register_id): Path<Uuid>,
+    Json(request): Json<OpenRegisterSessionRequest>,
+) -> Result<(StatusCode, Json<RegisterSession>), Error> {
+    let session = state.registers.open_session(auth.customer_id, register_id, request).await?;
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+/// GET /api/v1/pos/sessions/:id
+pub async fn get_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ZReport>, Error> {
+    Ok(Json(state.registers.session_report(auth.customer_id, session_id).await?))
+}
+
+/// GET /api/v1/pos/sessions/:id/transactions
+pub async fn list_transactions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<Vec<RegisterTransaction>>, Error> {
+    Ok(Json(state.registers.transactions(auth.customer_id, session_id).await?))
+}
+
+/// POST /api/v1/pos/sessions/:id/transactions
+pub async fn record_transaction(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<RecordRegisterTransactionRequest>,
+) -> Result<(StatusCode, Json<RegisterTransaction>), Error> {
+    let transaction = state.registers.record_transaction(auth.customer_id, session_id, request).await?;
+    Ok((StatusCode::CREATED, Json(transaction)))
+}
+
+/// POST /api/v1/pos/sessions/:id/close
+pub async fn close_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<CloseRegisterSessionRequest>,
+) -> Result<Json<ZReport>, Error> {
+    Ok(Json(state.registers.close_session(auth.customer_id, session_id, request).await?))
+}
+
+/// GET /api/v1/pos/sessions/:id/z-report
+pub async fn z_report(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<ZReportQuery>,
+) -> Result<Response, Error> {
+    let report = state.registers.z_report(auth.customer_id, session_id).await?;
+
+    match query.format.as_deref() {
+        None | Some("json") => Ok(Json(report).into_response()),
+        Some("csv") => {
+            let csv = report
+                .to_csv()
+                .map_err(|e| Error::Other(format!("Failed to export Z-report: {}", e)))?;
+            let filename = format!(
+                "attachment; filename=\"z-report-{}-{}.csv\"",
+                report.register_name.replace(|c: char| !c.is_ascii_alphanumeric(), "-").to_lowercase(),
+                report.closed_at.unwrap_or(report.opened_at).format("%Y%m%dT%H%M%SZ")
+            );
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, filename),
+                ],
+                csv,
+            )
+                .into_response())
+        }
+        Some(other) => Err(Error::validation(format!("Unsupported format '{}'; use json or csv", other))),
+    }
+}
+
+/// Router for POS register routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/pos/registers", get(list_registers).post(create_register))
+        .route("/pos/registers/:id/sessions", get(list_sessions).post(open_session))
+        .route("/pos/sessions/:id", get(get_session))
+        .route("/pos/sessions/:id/transactions", get(list_transactions).post(record_transaction))
+        .route("/pos/sessions/:id/close", post(close_session))
+        .route("/pos/sessions/:id/z-report", get(z_report))
+}
@@ -0,0 +1,172 @@
+//! Report Subscription API Routes
+//!
+//! Subscriptions email a sales summary, tax liability, low stock, failed
+//! payments or checkout funnel report (CSV, XLSX or PDF) to their recipients daily, weekly or
+//! monthly; the `reports` job sends them (`[reports]`):
+//! - GET    /api/v1/admin/reports/subscriptions             - Subscriptions
+//! - POST   /api/v1/admin/reports/subscriptions             - Subscribe recipients to a report
+//! - GET    /api/v1/admin/reports/subscriptions/:id         - Get a subscription
+//! - PUT    /api/v1/admin/reports/subscriptions/:id         - Update a subscription
+//! - DELETE /api/v1/admin/reports/subscriptions/:id         - Delete a subscription (its history is kept)
+//! - POST   /api/v1/admin/reports/subscriptions/:id/run     - Send the report now
+//! - GET    /api/v1/admin/reports/deliveries                - Delivery history (`?subscription_id=...`)
+//! - GET    /api/v1/admin/reports/deliveries/:id            - Get a delivery
+//! - GET    /api/v1/admin/reports/deliveries/:id/download   - Download a delivery's file
+//! - POST   /api/v1/admin/reports/deliveries/:id/resend     - Email a delivery again
+//!
+//! Report emails link to the public download route, which works until the
+//! file expires (`reports.retention_days`):
+//! - GET    /api/v1/reports/download/:token
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreateReportSubscriptionRequest, ReportDelivery, ReportSubscription, ResendReportRequest,
+    UpdateReportSubscriptionRequest,
+};
+use rcommerce_core::Error;
+
+/// Deliveries listed at most
+const LIST_LIMIT: i64 = 200;
+
+/// Query parameters for listing deliveries
+#[derive(Debug, Deserialize)]
+pub struct ListDeliveriesQuery {
+    pub subscription_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/reports/subscriptions
+pub async fn list_subscriptions(State(state): State<AppState>) -> Result<Json<Vec<ReportSubscription>>, Error> {
+    Ok(Json(state.reports.list_subscriptions().await?))
+}
+
+/// POST /api/v1/admin/reports/subscriptions
+pub async fn create_subscription(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateReportSubscriptionRequest>,
+) -> Result<(StatusCode, Json<ReportSubscription>), Error> {
+    let subscription = state.reports.create_subscription(Some(auth.customer_id), request).await?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+/// GET /api/v1/admin/reports/subscriptions/:id
+pub async fn get_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReportSubscription>, Error> {
+    Ok(Json(state.reports.get_subscription(id).await?))
+}
+
+/// PUT /api/v1/admin/reports/subscriptions/:id
+pub async fn update_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateReportSubscriptionRequest>,
+) -> Result<Json<ReportSubscription>, Error> {
+    Ok(Json(state.reports.update_subscription(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/reports/subscriptions/:id
+pub async fn delete_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.reports.delete_subscription(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/reports/subscriptions/:id/run
+pub async fn run_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReportDelivery>, Error> {
+    Ok(Json(state.reports.run_now(id).await?))
+}
+
+/// GET /api/v1/admin/reports/deliveries
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    Query(query): Query<ListDeliveriesQuery>,
+) -> Result<Json<Vec<ReportDelivery>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, LIST_LIMIT);
+    let deliveries = state.reports.list_deliveries(query.subscription_id, limit).await?;
+    Ok(Json(deliveries))
+}
+
+/// GET /api/v1/admin/reports/deliveries/:id
+pub async fn get_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReportDelivery>, Error> {
+    Ok(Json(state.reports.get_delivery(id).await?))
+}
+
+/// GET /api/v1/admin/reports/deliveries/:id/download
+pub async fn download_delivery(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response, Error> {
+    let (delivery, content) = state.reports.download(id).await?;
+    Ok(file_response(&delivery, content))
+}
+
+/// POST /api/v1/admin/reports/deliveries/:id/resend
+pub async fn resend_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ResendReportRequest>,
+) -> Result<Json<ReportDelivery>, Error> {
+    Ok(Json(state.reports.resend(id, request).await?))
+}
+
+/// GET /api/v1/reports/download/:token
+pub async fn download_report(State(state): State<AppState>, Path(token): Path<String>) -> Result<Response, Error> {
+    let (delivery, content) = state.reports.download_by_token(&token).await?;
+    Ok(file_response(&delivery, content))
+}
+
+fn file_response(delivery: &ReportDelivery, content: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, delivery.format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", delivery.filename),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+/// Admin router for report subscriptions and deliveries
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/reports/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route(
+            "/admin/reports/subscriptions/:id",
+            get(get_subscription).put(update_subscription).delete(delete_subscription),
+        )
+        .route("/admin/reports/subscriptions/:id/run", post(run_subscription))
+        .route("/admin/reports/deliveries", get(list_deliveries))
+        .route("/admin/reports/deliveries/:id", get(get_delivery))
+        .route("/admin/reports/deliveries/:id/download", get(download_delivery))
+        .route("/admin/reports/deliveries/:id/resend", post(resend_delivery))
+}
+
+/// Public router for the download links in report emails
+pub fn router() -> Router<AppState> {
+    Router::new().route("/reports/download/:token", get(download_report))
+}
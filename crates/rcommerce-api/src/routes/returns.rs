@@ -0,0 +1,176 @@
+//! Returns (RMA) API Routes
+//!
+//! Customers request returns for their own orders; staff approve, reject,
+//! receive and refund them. Approval creates a prepaid return label and
+//! receipt refunds the original payment (`[returns]`):
+//! - POST /api/v1/orders/:id/returns              - Request a return for order items
+//! - GET  /api/v1/returns                         - The customer's returns
+//! - GET  /api/v1/returns/:id                     - Return with its items and history
+//! - POST /api/v1/returns/:id/cancel              - Cancel a return before it is received
+//! - GET  /api/v1/admin/returns                   - All returns (`?status=requested` for the queue)
+//! - POST /api/v1/admin/returns/:id/approve       - Approve and create the return label
+//! - POST /api/v1/admin/returns/:id/reject        - Reject
+//! - POST /api/v1/admin/returns/:id/label         - (Re)create the return label, optionally with another carrier
+//! - POST /api/v1/admin/returns/:id/receive       - Mark the items received and refund them
+//! - POST /api/v1/admin/returns/:id/refund        - Retry a failed refund
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    CreateReturnRequest, ReturnAuthorization, ReturnDecision, ReturnDetails, ReturnLabelRequest, ReturnStatus,
+};
+use rcommerce_core::Error;
+
+/// Returns listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing returns
+#[derive(Debug, Deserialize)]
+pub struct ListReturnsQuery {
+    pub status: Option<ReturnStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Customers can only see their own returns unless admin
+fn authorize(auth: &JwtAuth, rma: &ReturnAuthorization) -> Result<(), Error> {
+    if rma.customer_id != Some(auth.customer_id) && !auth.is_admin() {
+        return Err(Error::not_found("Return not found"));
+    }
+    Ok(())
+}
+
+/// POST /api/v1/orders/:id/returns
+pub async fn request_return(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateReturnRequest>,
+) -> Result<(StatusCode, Json<ReturnDetails>), Error> {
+    // Admins can file returns for any customer's order
+    let customer_id = (!auth.is_admin()).then_some(auth.customer_id);
+    let rma = state.returns.request_return(order_id, customer_id, request).await?;
+    Ok((StatusCode::CREATED, Json(rma)))
+}
+
+/// GET /api/v1/returns
+pub async fn list_my_returns(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ListReturnsQuery>,
+) -> Result<Json<Vec<ReturnAuthorization>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    Ok(Json(state.returns.list(query.status, Some(auth.customer_id), limit).await?))
+}
+
+/// GET /api/v1/returns/:id
+pub async fn get_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<ReturnDetails>, Error> {
+    let rma = state.returns.get_details(id).await?;
+    authorize(&auth, &rma.rma)?;
+    Ok(Json(rma))
+}
+
+/// POST /api/v1/returns/:id/cancel
+pub async fn cancel_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    decision: Option<Json<ReturnDecision>>,
+) -> Result<Json<ReturnDetails>, Error> {
+    authorize(&auth, &state.returns.get(id).await?)?;
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.returns.cancel(id, Some(auth.customer_id), decision).await?))
+}
+
+/// GET /api/v1/admin/returns
+pub async fn list_returns(
+    State(state): State<AppState>,
+    Query(query): Query<ListReturnsQuery>,
+) -> Result<Json<Vec<ReturnAuthorization>>, Error> {
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    Ok(Json(state.returns.list(query.status, None, limit).await?))
+}
+
+/// POST /api/v1/admin/returns/:id/approve
+pub async fn approve_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    decision: Option<Json<ReturnDecision>>,
+) -> Result<Json<ReturnDetails>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.returns.approve(id, auth.customer_id, decision).await?))
+}
+
+/// POST /api/v1/admin/returns/:id/reject
+pub async fn reject_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    decision: Option<Json<ReturnDecision>>,
+) -> Result<Json<ReturnDetails>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.returns.reject(id, auth.customer_id, decision).await?))
+}
+
+/// POST /api/v1/admin/returns/:id/label
+pub async fn create_return_label(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    request: Option<Json<ReturnLabelRequest>>,
+) -> Result<Json<ReturnAuthorization>, Error> {
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    Ok(Json(state.returns.create_label(id, request).await?))
+}
+
+/// POST /api/v1/admin/returns/:id/receive
+pub async fn receive_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    decision: Option<Json<ReturnDecision>>,
+) -> Result<Json<ReturnDetails>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.returns.receive(id, auth.customer_id, decision).await?))
+}
+
+/// POST /api/v1/admin/returns/:id/refund
+pub async fn refund_return(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<ReturnDetails>, Error> {
+    Ok(Json(state.returns.refund(id, auth.customer_id).await?))
+}
+
+/// Router for customer return routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/orders/:id/returns", post(request_return))
+        .route("/returns", get(list_my_returns))
+        .route("/returns/:id", get(get_return))
+        .route("/returns/:id/cancel", post(cancel_return))
+}
+
+/// Router for return admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/returns", get(list_returns))
+        .route("/admin/returns/:id/approve", post(approve_return))
+        .route("/admin/returns/:id/reject", post(reject_return))
+        .route("/admin/returns/:id/label", post(create_return_label))
+        .route("/admin/returns/:id/receive", post(receive_return))
+        .route("/admin/returns/:id/refund", post(refund_return))
+}
@@ -0,0 +1,234 @@
+//! Staff Role API Routes
+//!
+//! Roles grant `resource:action` permissions (the API key scope format) on
+//! top of an account's base role; admin routes check them per route. Reading
+//! needs `users:read`, changing roles or assignments `users:admin`:
+//! - GET    /api/v1/admin/permissions                     - Permissions a role can grant
+//! - GET    /api/v1/admin/permissions/preview             - What a role, API key or account may do
+//! - GET    /api/v1/admin/roles                           - All roles
+//! - POST   /api/v1/admin/roles                           - Create a role
+//! - GET    /api/v1/admin/roles/:id                       - Get a role
+//! - PUT    /api/v1/admin/roles/:id                       - Update a role
+//! - DELETE /api/v1/admin/roles/:id                       - Delete a custom role
+//! - GET    /api/v1/admin/customers/:id/roles             - Roles assigned to an account
+//! - POST   /api/v1/admin/customers/:id/roles             - Assign a role
+//! - DELETE /api/v1/admin/customers/:id/roles/:role_id    - Remove a role
+//! - GET    /api/v1/admin/customers/:id/permissions       - An account's effective permissions
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::middleware::scopes::admin_route_access;
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::{
+    AssignRoleRequest, CreateRoleRequest, EffectivePermissions, PermissionPreview, PreviewSubject, Role,
+    RoleAssignment, UpdateRoleRequest,
+};
+use rcommerce_core::repository::ApiKeyRepository;
+use rcommerce_core::services::{permission_catalog, resource_access, Scope};
+use rcommerce_core::Error;
+
+/// GET /api/v1/admin/permissions
+pub async fn list_permissions() -> Json<Vec<String>> {
+    Json(permission_catalog())
+}
+
+/// Subject of a permissions preview; exactly one must be given
+#[derive(Debug, Default, Deserialize)]
+pub struct PreviewQuery {
+    pub role_id: Option<Uuid>,
+    /// API key prefix
+    pub api_key: Option<String>,
+    pub customer_id: Option<Uuid>,
+    /// Comma-separated permissions, e.g. to try a role before creating it
+    pub permissions: Option<String>,
+}
+
+/// GET /api/v1/admin/permissions/preview
+///
+/// Evaluates permissions the way the auth middleware would, without
+/// issuing tokens or acting as the subject.
+pub async fn preview_permissions(
+    State(state): State<AppState>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Json<PermissionPreview>, Error> {
+    let given = [
+        query.role_id.is_some(),
+        query.api_key.is_some(),
+        query.customer_id.is_some(),
+        query.permissions.is_some(),
+    ];
+    if given.iter().filter(|given| **given).count() != 1 {
+        return Err(Error::validation(
+            "Give exactly one of role_id, api_key, customer_id or permissions",
+        ));
+    }
+
+    let mut notes = Vec::new();
+    let (subject, permissions, staff) = if let Some(id) = query.role_id {
+        let role = state.roles.get_role(id).await?;
+        notes.push("Accounts holding this role also keep their base role's permissions".to_string());
+        (PreviewSubject::Role { id, name: role.name }, role.permissions, true)
+    } else if let Some(ref prefix) = query.api_key {
+        let key = state
+            .api_key_repository
+            .find_by_prefix(prefix)
+            .await?
+            .ok_or_else(|| Error::not_found("API key not found"))?;
+        notes.push("API keys cannot call admin routes".to_string());
+        if !key.is_active || key.revoked_at.is_some() {
+            notes.push("Key is revoked; every request is rejected".to_string());
+        }
+        if key.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+            notes.push("Key has expired; every request is rejected".to_string());
+        }
+        if key.is_publishable() {
+            notes.push("Publishable key: storefront routes only, from its allowed origins".to_string());
+        }
+        if !key.allowed_ips.is_empty() {
+            notes.push(format!("Only accepted from {}", key.allowed_ips.join(", ")));
+        }
+        let subject = PreviewSubject::ApiKey {
+            prefix: key.key_prefix,
+            name: key.name,
+            key_type: key.key_type.as_str().to_string(),
+        };
+        (subject, key.scopes, false)
+    } else if let Some(id) = query.customer_id {
+        let effective = state
+            .roles
+            .effective_permissions(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Customer not found"))?;
+        (PreviewSubject::Customer { id }, effective.permissions, true)
+    } else {
+        let permissions: Vec<String> = query
+            .permissions
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+        for permission in &permissions {
+            Scope::parse(permission).map_err(Error::validation)?;
+        }
+        (PreviewSubject::Permissions, permissions, true)
+    };
+
+    for permission in &permissions {
+        if Scope::parse(permission).is_err() {
+            notes.push(format!("Unrecognised scope '{}': nothing is granted until it is removed", permission));
+        }
+    }
+
+    Ok(Json(PermissionPreview {
+        is_admin: permissions.iter().any(|p| p == "admin"),
+        resources: resource_access(&permissions, staff),
+        admin_routes: if staff { admin_route_access(&permissions) } else { Vec::new() },
+        subject,
+        permissions,
+        notes,
+    }))
+}
+
+/// GET /api/v1/admin/roles
+pub async fn list_roles(State(state): State<AppState>) -> Result<Json<Vec<Role>>, Error> {
+    Ok(Json(state.roles.list_roles().await?))
+}
+
+/// POST /api/v1/admin/roles
+pub async fn create_role(
+    State(state): State<AppState>,
+    Json(request): Json<CreateRoleRequest>,
+) -> Result<(StatusCode, Json<Role>), Error> {
+    let role = state.roles.create_role(request).await?;
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+/// GET /api/v1/admin/roles/:id
+pub async fn get_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Role>, Error> {
+    Ok(Json(state.roles.get_role(id).await?))
+}
+
+/// PUT /api/v1/admin/roles/:id
+pub async fn update_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateRoleRequest>,
+) -> Result<Json<Role>, Error> {
+    Ok(Json(state.roles.update_role(id, request).await?))
+}
+
+/// DELETE /api/v1/admin/roles/:id
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.roles.delete_role(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/customers/:id/roles
+pub async fn list_assignments(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+) -> Result<Json<Vec<RoleAssignment>>, Error> {
+    Ok(Json(state.roles.assignments(customer_id).await?))
+}
+
+/// POST /api/v1/admin/customers/:id/roles
+pub async fn assign_role(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<AssignRoleRequest>,
+) -> Result<Json<Vec<RoleAssignment>>, Error> {
+    Ok(Json(state.roles.assign(customer_id, request.role_id, auth.customer_id).await?))
+}
+
+/// DELETE /api/v1/admin/customers/:id/roles/:role_id
+pub async fn unassign_role(
+    State(state): State<AppState>,
+    Path((customer_id, role_id)): Path<(Uuid, Uuid)>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<StatusCode, Error> {
+    state.roles.unassign(customer_id, role_id, auth.customer_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/customers/:id/permissions
+pub async fn get_permissions(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+) -> Result<Json<EffectivePermissions>, Error> {
+    let permissions = state
+        .roles
+        .effective_permissions(customer_id)
+        .await?
+        .ok_or_else(|| Error::not_found("Customer not found"))?;
+    Ok(Json(permissions))
+}
+
+/// Router for role admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/permissions", get(list_permissions))
+        .route("/admin/permissions/preview", get(preview_permissions))
+        .route("/admin/roles", get(list_roles).post(create_role))
+        .route("/admin/roles/:id", get(get_role).put(update_role).delete(delete_role))
+        .route("/admin/customers/:id/roles", get(list_assignments).post(assign_role))
+        .route("/admin/customers/:id/roles/:role_id", delete(unassign_role))
+        .route("/admin/customers/:id/permissions", get(get_permissions))
+}
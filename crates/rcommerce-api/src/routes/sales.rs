@@ -0,0 +1,134 @@
+//! Sales Analytics API Routes
+//!
+//! Read from daily rollups refreshed by the `sales_views_refresh` job, so
+//! figures lag orders by up to `[reports] sales_refresh_interval_secs`
+//! (`refreshed_at` in each response). Ranges are UTC days, both inclusive
+//! (`?from=2026-10-01&to=2026-10-31`, default the last 30 days); amounts are
+//! in the base currency:
+//! - GET /api/v1/admin/sales/revenue             - Orders, items and revenue per period (`?granularity=day|week|month`)
+//! - GET /api/v1/admin/sales/top-products        - Best selling products (`?by=revenue|units&limit=10`)
+//! - GET /api/v1/admin/sales/channels            - Orders, payment rate and storefront conversion per channel
+//! - GET /api/v1/admin/sales/refunds             - Refund rates per period (`?granularity=`)
+//! - GET /api/v1/admin/sales/average-order-value - Average order value per period (`?granularity=`)
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::state::AppState;
+use rcommerce_core::reports::sales::{OrderValueFigures, RefundFigures, RevenueFigures};
+use rcommerce_core::reports::{ChannelReport, Granularity, SalesRange, SalesReport, TopProductsBy, TopProductsReport};
+use rcommerce_core::Error;
+
+/// Days covered when the request names no range
+const DEFAULT_DAYS: i64 = 30;
+
+/// Query parameters of the sales reports
+#[derive(Debug, Default, Deserialize)]
+pub struct SalesQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub granularity: Granularity,
+}
+
+impl SalesQuery {
+    /// The requested range; `to` defaults to today and `from` to 30 days
+    /// before `to`
+    fn range(&self, today: NaiveDate) -> Result<SalesRange, Error> {
+        let to = self.to.unwrap_or(today);
+        let from = self.from.unwrap_or(to - Duration::days(DEFAULT_DAYS - 1));
+        SalesRange::new(from, to)
+    }
+}
+
+/// Query parameters of the top products report
+#[derive(Debug, Deserialize)]
+pub struct TopProductsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub by: TopProductsBy,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/sales/revenue
+pub async fn revenue(
+    State(state): State<AppState>,
+    Query(query): Query<SalesQuery>,
+) -> Result<Json<SalesReport<RevenueFigures>>, Error> {
+    let range = query.range(Utc::now().date_naive())?;
+    Ok(Json(state.sales.revenue(range, query.granularity).await?))
+}
+
+/// GET /api/v1/admin/sales/top-products
+pub async fn top_products(
+    State(state): State<AppState>,
+    Query(query): Query<TopProductsQuery>,
+) -> Result<Json<TopProductsReport>, Error> {
+    let range = SalesQuery { from: query.from, to: query.to, ..Default::default() }.range(Utc::now().date_naive())?;
+    Ok(Json(state.sales.top_products(range, query.by, query.limit.unwrap_or(10)).await?))
+}
+
+/// GET /api/v1/admin/sales/channels
+pub async fn channels(
+    State(state): State<AppState>,
+    Query(query): Query<SalesQuery>,
+) -> Result<Json<ChannelReport>, Error> {
+    let range = query.range(Utc::now().date_naive())?;
+    Ok(Json(state.sales.channels(range).await?))
+}
+
+/// GET /api/v1/admin/sales/refunds
+pub async fn refunds(
+    State(state): State<AppState>,
+    Query(query): Query<SalesQuery>,
+) -> Result<Json<SalesReport<RefundFigures>>, Error> {
+    let range = query.range(Utc::now().date_naive())?;
+    Ok(Json(state.sales.refunds(range, query.granularity).await?))
+}
+
+/// GET /api/v1/admin/sales/average-order-value
+pub async fn average_order_value(
+    State(state): State<AppState>,
+    Query(query): Query<SalesQuery>,
+) -> Result<Json<SalesReport<OrderValueFigures>>, Error> {
+    let range = query.range(Utc::now().date_naive())?;
+    Ok(Json(state.sales.average_order_value(range, query.granularity).await?))
+}
+
+/// Admin router for sales analytics
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/sales/revenue", get(revenue))
+        .route("/admin/sales/top-products", get(top_products))
+        .route("/admin/sales/channels", get(channels))
+        .route("/admin/sales/refunds", get(refunds))
+        .route("/admin/sales/average-order-value", get(average_order_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_default_range() {
+        let today = date("2026-10-16");
+        let range = SalesQuery::default().range(today).unwrap();
+        assert_eq!((range.from, range.to), (date("2026-09-17"), today));
+
+        let query = SalesQuery { to: Some(date("2026-09-30")), ..Default::default() };
+        assert_eq!(query.range(today).unwrap().from, date("2026-09-01"));
+
+        let query = SalesQuery { from: Some(date("2026-11-01")), ..Default::default() };
+        assert!(query.range(today).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Shipment API Routes
+//!
+//! Orders ship complete or partial by their shipping preference, else the
+//! customer's, else `fulfillment.default_shipping_preference`. Fulfilling
+//! an order splits it across warehouses and backorders what is out of
+//! stock; backorders ship when restocked (`[fulfillment]`). Labels are
+//! bought on the carrier account of the location a shipment leaves from
+//! (`[[shipping.locations]]`) and kept in media storage (`[media]`) as
+//! the PDF, ZPL or PNG the carrier sent:
+//! - PUT  /api/v1/customers/me/shipping-preference           - The customer's preference for new orders
+//! - PUT  /api/v1/orders/:id/shipping-preference             - Preference for one of the customer's orders
+//! - GET  /api/v1/admin/orders/:id/fulfillment-plan          - How the order would ship now
+//! - GET  /api/v1/admin/orders/:id/shipments                 - Shipments and backorders
+//! - POST /api/v1/admin/orders/:id/shipments                 - Ship what the plan allows, backorder the rest
+//! - PUT  /api/v1/admin/orders/:id/shipping-preference       - Set an order's preference
+//! - PUT  /api/v1/admin/customers/:id/shipping-preference    - Set a customer's preference
+//! - POST /api/v1/admin/shipments/:id/label                  - Buy a shipping label
+//! - GET  /api/v1/admin/shipments/:id/label                  - Download the stored label (?format=pdf|zpl|png)
+//! - POST /api/v1/admin/shipments/labels/batch               - Labels of a pick list of orders (ZPL or ZIP)
+//! - POST /api/v1/admin/backorders/release                   - Ship backorders that are back in stock now
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::order::{
+    BackorderReport, BatchLabelRequest, FulfillmentPlan, LabelFile, LabelFormat, OrderShipments,
+    SetShippingPreferenceRequest, Shipment, ShipmentLabelRequest,
+};
+use rcommerce_core::Error;
+
+/// PUT /api/v1/customers/me/shipping-preference
+pub async fn set_my_preference(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<SetShippingPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let preference = state
+        .shipments
+        .set_customer_preference(auth.customer_id, request.shipping_preference)
+        .await?;
+    Ok(Json(json!({ "shipping_preference": preference })))
+}
+
+/// PUT /api/v1/orders/:id/shipping-preference
+pub async fn set_my_order_preference(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<SetShippingPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let customer_id = (!auth.is_admin()).then_some(auth.customer_id);
+    let preference = state
+        .shipments
+        .set_order_preference(order_id, customer_id, request.shipping_preference)
+        .await?;
+    Ok(Json(json!({ "shipping_preference": preference })))
+}
+
+/// GET /api/v1/admin/orders/:id/fulfillment-plan
+pub async fn get_plan(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<FulfillmentPlan>, Error> {
+    Ok(Json(state.shipments.plan_order(order_id).await?))
+}
+
+/// GET /api/v1/admin/orders/:id/shipments
+pub async fn list_shipments(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<OrderShipments>, Error> {
+    Ok(Json(state.shipments.order_shipments(order_id).await?))
+}
+
+/// POST /api/v1/admin/orders/:id/shipments
+pub async fn fulfill_order(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<OrderShipments>), Error> {
+    let shipments = state.shipments.fulfill_order(order_id).await?;
+    Ok((StatusCode::CREATED, Json(shipments)))
+}
+
+/// PUT /api/v1/admin/orders/:id/shipping-preference
+pub async fn set_order_preference(
+    State(state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+    Json(request): Json<SetShippingPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let preference = state
+        .shipments
+        .set_order_preference(order_id, None, request.shipping_preference)
+        .await?;
+    Ok(Json(json!({ "shipping_preference": preference })))
+}
+
+/// PUT /api/v1/admin/customers/:id/shipping-preference
+pub async fn set_customer_preference(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Json(request): Json<SetShippingPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let preference = state
+        .shipments
+        .set_customer_preference(customer_id, request.shipping_preference)
+        .await?;
+    Ok(Json(json!({ "shipping_preference": preference })))
+}
+
+/// POST /api/v1/admin/shipments/:id/label
+pub async fn create_label(
+    State(state): State<AppState>,
+    Path(shipment_id): Path<Uuid>,
+    Json(request): Json<ShipmentLabelRequest>,
+) -> Result<Json<Shipment>, Error> {
+    Ok(Json(state.shipments.create_label(shipment_id, request).await?))
+}
+
+/// Query parameters for downloading a label
+#[derive(Debug, Deserialize)]
+pub struct LabelQuery {
+    pub format: Option<LabelFormat>,
+}
+
+fn label_response(label: LabelFile) -> impl IntoResponse {
+    let disposition = format!("inline; filename=\"{}\"", label.filename);
+    (
+        [
+            (header::CONTENT_TYPE, label.content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        label.data,
+    )
+}
+
+/// GET /api/v1/admin/shipments/:id/label
+pub async fn get_label(
+    State(state): State<AppState>,
+    Path(shipment_id): Path<Uuid>,
+    Query(query): Query<LabelQuery>,
+) -> Result<impl IntoResponse, Error> {
+    Ok(label_response(state.shipments.label(shipment_id, query.format).await?))
+}
+
+/// POST /api/v1/admin/shipments/labels/batch
+pub async fn batch_labels(
+    State(state): State<AppState>,
+    Json(request): Json<BatchLabelRequest>,
+) -> Result<impl IntoResponse, Error> {
+    Ok(label_response(state.shipments.batch_labels(request).await?))
+}
+
+/// POST /api/v1/admin/backorders/release
+pub async fn release_backorders(State(state): State<AppState>) -> Result<Json<BackorderReport>, Error> {
+    Ok(Json(state.shipments.release_backorders().await?))
+}
+
+/// Router for customer shipping preference routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/customers/me/shipping-preference", put(set_my_preference))
+        .route("/orders/:id/shipping-preference", put(set_my_order_preference))
+}
+
+/// Router for shipment admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/orders/:id/fulfillment-plan", get(get_plan))
+        .route("/admin/orders/:id/shipments", get(list_shipments).post(fulfill_order))
+        .route("/admin/orders/:id/shipping-preference", put(set_order_preference))
+        .route("/admin/customers/:id/shipping-preference", put(set_customer_preference))
+        .route("/admin/shipments/:id/label", get(get_label).post(create_label))
+        .route("/admin/shipments/labels/batch", post(batch_labels))
+        .route("/admin/backorders/release", post(release_backorders))
+}
@@ -0,0 +1,80 @@
+//! Soft Delete API Routes
+//!
+//! Deleted products, customers and orders are kept for `[soft_delete]
+//! retention_days` before the purge job removes them:
+//! - DELETE /api/v1/admin/{products,customers,orders}/:id         - Soft-delete
+//! - POST   /api/v1/admin/{products,customers,orders}/:id/restore - Restore
+//! - GET    /api/v1/admin/{products,customers,orders}/deleted     - Deleted records (`?limit=`)
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{DeletedRecord, SoftDeleteKind};
+use rcommerce_core::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct DeletedQuery {
+    pub limit: Option<i64>,
+}
+
+/// DELETE /api/v1/admin/{kind}/:id
+pub async fn soft_delete(
+    state: AppState,
+    kind: SoftDeleteKind,
+    id: Uuid,
+) -> Result<StatusCode, Error> {
+    state.soft_deletes.delete(kind, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/{kind}/:id/restore
+pub async fn restore(
+    state: AppState,
+    kind: SoftDeleteKind,
+    id: Uuid,
+) -> Result<StatusCode, Error> {
+    state.soft_deletes.restore(kind, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/admin/{kind}/deleted
+pub async fn list_deleted(
+    state: AppState,
+    kind: SoftDeleteKind,
+    query: DeletedQuery,
+) -> Result<Json<Vec<DeletedRecord>>, Error> {
+    let limit = query.limit.unwrap_or(100);
+    Ok(Json(state.soft_deletes.list_deleted(kind, limit).await?))
+}
+
+/// Delete, restore and deleted-listing routes for one kind
+fn kind_router(kind: SoftDeleteKind) -> Router<AppState> {
+    let base = format!("/admin/{}", kind.as_str());
+    Router::new()
+        .route(
+            &format!("{}/:id", base),
+            delete(move |State(state): State<AppState>, Path(id): Path<Uuid>| soft_delete(state, kind, id)),
+        )
+        .route(
+            &format!("{}/:id/restore", base),
+            post(move |State(state): State<AppState>, Path(id): Path<Uuid>| restore(state, kind, id)),
+        )
+        .route(
+            &format!("{}/deleted", base),
+            get(move |State(state): State<AppState>, Query(query): Query<DeletedQuery>| list_deleted(state, kind, query)),
+        )
+}
+
+/// Router for soft delete routes
+pub fn router() -> Router<AppState> {
+    SoftDeleteKind::ALL
+        .into_iter()
+        .fold(Router::new(), |router, kind| router.merge(kind_router(kind)))
+}
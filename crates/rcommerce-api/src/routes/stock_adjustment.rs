@@ -0,0 +1,212 @@
+//! Stock Adjustment API Routes
+//!
+//! Manual stock corrections by staff, with a second approval for large
+//! changes (`[stock_adjustments]` thresholds):
+//! - POST /api/v1/inventory/adjustments              - Request an adjustment (posted at once if below the thresholds)
+//! - GET  /api/v1/inventory/adjustments              - Recent adjustments (`?status=pending` for the approval queue)
+//! - GET  /api/v1/inventory/adjustments/:id          - Adjustment with its audit trail
+//! - POST /api/v1/inventory/adjustments/:id/approve  - Approve and post as a stock movement (approver roles)
+//! - POST /api/v1/inventory/adjustments/:id/reject   - Reject (approver roles)
+//! - POST /api/v1/inventory/adjustments/:id/cancel   - Withdraw (requester or approver)
+//! - GET  /api/v1/admin/inventory/locations          - Active inventory locations
+//! - GET  /api/v1/admin/inventory/levels?sku=        - Stock of a product (by ID or SKU) at each active location
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::inventory::{
+    CreateStockAdjustmentRequest, StockAdjustmentDecision, StockAdjustmentRequest, StockAdjustmentStatus,
+};
+use rcommerce_core::repository::StockAdjustmentRepository;
+use rcommerce_core::Error;
+
+/// Adjustments listed at most
+const LIST_LIMIT: i64 = 100;
+
+/// Query parameters for listing adjustments
+#[derive(Debug, Deserialize)]
+pub struct ListAdjustmentsQuery {
+    pub status: Option<StockAdjustmentStatus>,
+    pub limit: Option<i64>,
+}
+
+/// POST /api/v1/inventory/adjustments
+pub async fn request_adjustment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(request): Json<CreateStockAdjustmentRequest>,
+) -> Result<(StatusCode, Json<StockAdjustmentRequest>), Error> {
+    let adjustment = state.stock_adjustments.request(auth.customer_id, request).await?;
+    let status = if adjustment.status == StockAdjustmentStatus::Pending {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(adjustment)))
+}
+
+/// GET /api/v1/inventory/adjustments
+pub async fn list_adjustments(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Query(query): Query<ListAdjustmentsQuery>,
+) -> Result<Json<Vec<StockAdjustmentRequest>>, Error> {
+    require_staff(&state, &auth).await?;
+    let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(1, LIST_LIMIT);
+    Ok(Json(state.stock_adjustments.repository().list(query.status, limit).await?))
+}
+
+/// GET /api/v1/inventory/adjustments/:id
+pub async fn get_adjustment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, Error> {
+    require_staff(&state, &auth).await?;
+    let adjustment = state.stock_adjustments.get(id).await?;
+    let audit_trail = state.stock_adjustments.repository().audit_trail(id).await?;
+    Ok(Json(serde_json::json!({
+        "adjustment": adjustment,
+        "audit_trail": audit_trail,
+    })))
+}
+
+/// POST /api/v1/inventory/adjustments/:id/approve
+pub async fn approve_adjustment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+    decision: Option<Json<StockAdjustmentDecision>>,
+) -> Result<Json<StockAdjustmentRequest>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.stock_adjustments.approve(auth.customer_id, id, decision).await?))
+}
+
+/// POST /api/v1/inventory/adjustments/:id/reject
+pub async fn reject_adjustment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+    decision: Option<Json<StockAdjustmentDecision>>,
+) -> Result<Json<StockAdjustmentRequest>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.stock_adjustments.reject(auth.customer_id, id, decision).await?))
+}
+
+/// POST /api/v1/inventory/adjustments/:id/cancel
+pub async fn cancel_adjustment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(id): Path<Uuid>,
+    decision: Option<Json<StockAdjustmentDecision>>,
+) -> Result<Json<StockAdjustmentRequest>, Error> {
+    let decision = decision.map(|Json(d)| d).unwrap_or_default();
+    Ok(Json(state.stock_adjustments.cancel(auth.customer_id, id, decision).await?))
+}
+
+/// Query parameters for stock levels: a product ID or a product or variant SKU
+#[derive(Debug, Deserialize)]
+pub struct StockLevelsQuery {
+    pub product_id: Option<Uuid>,
+    pub sku: Option<String>,
+}
+
+/// An active inventory location
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InventoryLocationView {
+    pub id: Uuid,
+    pub name: String,
+    pub code: String,
+}
+
+/// Stock of a product (or variant) at a location
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StockLevelView {
+    pub product_id: Uuid,
+    pub product_title: String,
+    pub variant_id: Option<Uuid>,
+    pub variant_title: Option<String>,
+    pub sku: Option<String>,
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub available: i32,
+    pub reserved: i32,
+    pub incoming: i32,
+}
+
+/// GET /api/v1/admin/inventory/locations
+pub async fn list_locations(State(state): State<AppState>) -> Result<Json<Vec<InventoryLocationView>>, Error> {
+    sqlx::query_as::<_, InventoryLocationView>(
+        "SELECT id, name, code FROM inventory_locations WHERE is_active ORDER BY name",
+    )
+    .fetch_all(state.db.pool())
+    .await
+    .map(Json)
+    .map_err(|e| Error::Other(format!("Failed to list inventory locations: {}", e)))
+}
+
+/// GET /api/v1/admin/inventory/levels?product_id= or ?sku=
+pub async fn list_levels(
+    State(state): State<AppState>,
+    Query(query): Query<StockLevelsQuery>,
+) -> Result<Json<Vec<StockLevelView>>, Error> {
+    let sku = query.sku.as_deref().map(str::trim).filter(|sku| !sku.is_empty());
+    if query.product_id.is_none() && sku.is_none() {
+        return Err(Error::validation("product_id or sku is required"));
+    }
+    sqlx::query_as::<_, StockLevelView>(
+        r#"
+        SELECT il.product_id, p.title AS product_title, il.variant_id, v.title AS variant_title,
+               COALESCE(v.sku, p.sku) AS sku, l.id AS location_id, l.name AS location_name,
+               il.available_quantity AS available, il.reserved_quantity AS reserved,
+               il.incoming_quantity AS incoming
+        FROM inventory_levels il
+        JOIN products p ON p.id = il.product_id
+        LEFT JOIN product_variants v ON v.id = il.variant_id
+        JOIN inventory_locations l ON l.id = il.location_id
+        WHERE l.is_active
+          AND (il.product_id = $1 OR p.sku = $2 OR v.sku = $2)
+        ORDER BY p.title, v.title NULLS FIRST, l.name
+        LIMIT 200
+        "#,
+    )
+    .bind(query.product_id)
+    .bind(sku)
+    .fetch_all(state.db.pool())
+    .await
+    .map(Json)
+    .map_err(|e| Error::Other(format!("Failed to get stock levels: {}", e)))
+}
+
+/// Adjustments are only visible to staff
+async fn require_staff(state: &AppState, auth: &JwtAuth) -> Result<(), Error> {
+    match state.stock_adjustments.repository().actor_role(auth.customer_id).await? {
+        Some(role) if role != rcommerce_core::models::CustomerRole::Customer => Ok(()),
+        _ => Err(Error::HttpError(StatusCode::FORBIDDEN, "Only staff can view stock adjustments".to_string())),
+    }
+}
+
+/// Router for stock adjustment routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/inventory/adjustments", get(list_adjustments).post(request_adjustment))
+        .route("/inventory/adjustments/:id", get(get_adjustment))
+        .route("/inventory/adjustments/:id/approve", post(approve_adjustment))
+        .route("/inventory/adjustments/:id/reject", post(reject_adjustment))
+        .route("/inventory/adjustments/:id/cancel", post(cancel_adjustment))
+}
+
+/// Inventory lookups for staff (mounted under the admin routes)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/inventory/locations", get(list_locations))
+        .route("/admin/inventory/levels", get(list_levels))
+}
@@ -0,0 +1,162 @@
+//! Storefront Stock Events
+//!
+//! Product pages keep their stock badges current with server-sent events:
+//! - GET /api/v1/storefront/stock/events?products=<id>,<id> - Availability stream (`EventSource`)
+//!
+//! The stream starts with an `in_stock` or `out_of_stock` event for each
+//! product, then sends `out_of_stock` and `back_in_stock` as products and
+//! their variants change (`variant_id` is set for variants). Events come
+//! from the same bus as the admin live events socket; quantities are never
+//! sent. The publishable key can be passed as `?key=`, since `EventSource`
+//! can't set headers.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::websocket::{StockStatus, StockUpdate};
+use rcommerce_core::Error;
+
+/// Products one stream may follow
+pub const MAX_PRODUCTS: usize = 100;
+
+/// Events buffered for a slow client before it is treated as lagging
+const STREAM_BUFFER: usize = 64;
+
+#[derive(Debug, Deserialize)]
+pub struct StockEventsQuery {
+    /// Comma-separated product IDs
+    #[serde(default)]
+    pub products: String,
+}
+
+fn parse_product_ids(products: &str) -> Result<Vec<Uuid>, Error> {
+    let mut ids = Vec::new();
+    for id in products.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = Uuid::parse_str(id).map_err(|_| Error::validation(format!("Invalid product ID: {}", id)))?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() {
+        return Err(Error::validation("products is required"));
+    }
+    if ids.len() > MAX_PRODUCTS {
+        return Err(Error::validation(format!("At most {} products per stream", MAX_PRODUCTS)));
+    }
+    Ok(ids)
+}
+
+/// Current availability of the products that are on sale
+async fn current_stock(pool: &PgPool, product_ids: &[Uuid]) -> Result<Vec<StockUpdate>, Error> {
+    let rows: Vec<(Uuid, bool)> = sqlx::query_as(
+        r#"
+        SELECT id, stock_available(inventory_management, inventory_policy, inventory_quantity, continues_selling_when_out_of_stock)
+        FROM products
+        WHERE id = ANY($1) AND is_active AND deleted_at IS NULL
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to load stock: {}", e)))?;
+    Ok(rows
+        .into_iter()
+        .map(|(product_id, available)| StockUpdate { product_id, variant_id: None, status: StockStatus::current(available) })
+        .collect())
+}
+
+fn stock_event(update: &StockUpdate) -> Event {
+    Event::default()
+        .event("stock")
+        .json_data(update)
+        .unwrap_or_else(|_| Event::default().event("stock"))
+}
+
+/// GET /api/v1/storefront/stock/events
+pub async fn stock_events(
+    State(state): State<AppState>,
+    Query(query): Query<StockEventsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, Error> {
+    if !state.websocket.enabled {
+        return Err(Error::not_found("Stock events are disabled"));
+    }
+    let product_ids = parse_product_ids(&query.products)?;
+    if state.live_events.connection_count() >= state.websocket.max_connections {
+        return Err(Error::HttpError(StatusCode::SERVICE_UNAVAILABLE, "Too many live connections".to_string()));
+    }
+
+    // Subscribe before reading the current stock so no change falls in between
+    let mut events = state.live_events.subscribe();
+    let pool = state.db.pool().clone();
+    let initial = current_stock(&pool, &product_ids).await?;
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        for update in &initial {
+            if tx.send(Ok(stock_event(update))).await.is_err() {
+                return;
+            }
+        }
+        loop {
+            let updates: Vec<StockUpdate> = tokio::select! {
+                _ = tx.closed() => return,
+                event = events.recv() => match event {
+                    Ok(event) => StockUpdate::from_event(&event)
+                        .filter(|update| product_ids.contains(&update.product_id))
+                        .into_iter()
+                        .collect(),
+                    // Missed events: send the current stock again instead
+                    Err(RecvError::Lagged(_)) => match current_stock(&pool, &product_ids).await {
+                        Ok(updates) => updates,
+                        Err(e) => {
+                            tracing::warn!("Failed to resync stock events: {}", e);
+                            Vec::new()
+                        }
+                    },
+                    Err(RecvError::Closed) => return,
+                },
+            };
+            for update in &updates {
+                if tx.send(Ok(stock_event(update))).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::new().interval(state.websocket.ping_interval())))
+}
+
+/// Router for storefront stock events (publishable key)
+pub fn router() -> Router<AppState> {
+    Router::new().route("/storefront/stock/events", get(stock_events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_product_ids() {
+        let id = Uuid::new_v4();
+        assert_eq!(parse_product_ids(&format!("{}, {},", id, id)).unwrap(), vec![id]);
+        assert!(parse_product_ids("").is_err());
+        assert!(parse_product_ids("not-a-uuid").is_err());
+
+        let too_many: Vec<String> = (0..=MAX_PRODUCTS).map(|_| Uuid::new_v4().to_string()).collect();
+        assert!(parse_product_ids(&too_many.join(",")).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! Storefront API Routes
+//!
+//! Read-only catalog endpoints and analytics events for storefront
+//! JavaScript, authenticated with a publishable key (`X-Storefront-Key`
+//! header or `?key=`) from one of the key's allowed origins. Secret keys and
+//! JWTs are not accepted here.
+//! - GET /api/v1/storefront/products                         - List products
+//! - GET /api/v1/storefront/products/:id                     - Get product
+//! - GET /api/v1/storefront/products/:id/variants            - Variants with their option values
+//! - GET /api/v1/storefront/products/:id/variants/:variant_id - Variant details
+//! - GET /api/v1/storefront/products/:id/options             - Option names with the values in use
+//! - GET /api/v1/storefront/products/:id/price               - Price in a currency (price lists, FX)
+//! - GET /api/v1/storefront/products/by-slug/:slug           - Get product by slug (301 for an old slug)
+//! - GET /api/v1/storefront/redirects/resolve                - Where a storefront path moved (`?path=`)
+//! - POST /api/v1/storefront/events                          - Track funnel events (see `analytics`)
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::routes::{analytics, price_list, product, redirects, variant};
+use crate::state::AppState;
+
+/// Router for publishable-key storefront routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/storefront/products", get(product::list_products))
+        .route("/storefront/products/:id", get(product::get_product))
+        .route("/storefront/products/:id/variants", get(variant::list_variants))
+        .route("/storefront/products/:id/variants/:variant_id", get(variant::get_variant))
+        .route("/storefront/products/:id/options", get(variant::list_options))
+        .route("/storefront/products/:id/price", get(price_list::get_product_price))
+        .route("/storefront/products/by-slug/:slug", get(redirects::get_product_by_slug))
+        .route("/storefront/redirects/resolve", get(redirects::resolve))
+        .route("/storefront/events", post(analytics::track_events))
+}
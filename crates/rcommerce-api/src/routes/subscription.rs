@@ -12,12 +12,16 @@ use axum::{
     routing::{get, post, put},
     Json, Router,
 };
+use async_trait::async_trait;
 use serde::Deserialize;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::middleware::JwtAuth;
 use crate::state::AppState;
-use rcommerce_core::repository::SubscriptionRepository;
+use rcommerce_core::jobs::recurring::{interval_schedule, RecurringJob};
+use rcommerce_core::repository::{PostgresSubscriptionRepository, SubscriptionRepository};
+use rcommerce_core::subscriptions::BillingEngine;
 use rcommerce_core::models::{
     CreateSubscriptionRequest, UpdateSubscriptionRequest, CancelSubscriptionRequest,
     SubscriptionFilter, SubscriptionStatus,
@@ -261,12 +265,15 @@ async fn admin_get_summary(
 async fn admin_process_billing(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    match state.subscription_service.process_due_subscriptions().await {
-        Ok(invoices) => {
+    match state.subscription_billing.run().await {
+        Ok(report) => {
             Ok(Json(serde_json::json!({
                 "success": true,
-                "message": format!("Processed {} subscriptions", invoices.len()),
-                "invoices_created": invoices.len()
+                "message": format!(
+                    "Renewed {} subscriptions, {} failed, {} expired",
+                    report.renewed, report.failed, report.expired
+                ),
+                "report": report
             })))
         }
         Err(e) => {
@@ -276,6 +283,47 @@ async fn admin_process_billing(
     }
 }
 
+/// Subscription renewals and dunning retries as a recurring job
+pub struct BillingJob {
+    billing: Arc<BillingEngine<PostgresSubscriptionRepository>>,
+}
+
+#[async_trait]
+impl RecurringJob for BillingJob {
+    fn name(&self) -> &str {
+        "subscription_billing"
+    }
+
+    fn description(&self) -> &str {
+        "Renew due subscriptions and charge due dunning retries"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.billing.config().interval_secs)
+    }
+
+    async fn run(&self) -> rcommerce_core::Result<String> {
+        let report = self.billing.run().await?;
+        let retries = report
+            .retries
+            .map(|retries| format!("; {} of {} retries succeeded", retries.succeeded, retries.processed))
+            .unwrap_or_default();
+        Ok(format!(
+            "{} renewed, {} failed, {} expired, {} errors{}",
+            report.renewed, report.failed, report.expired, report.errors, retries
+        ))
+    }
+}
+
+/// The billing job, when subscription billing is enabled
+pub fn billing_job(state: &AppState) -> Option<Arc<dyn RecurringJob>> {
+    let billing = state.subscription_billing.clone();
+    if !billing.config().enabled {
+        return None;
+    }
+    Some(Arc::new(BillingJob { billing }))
+}
+
 /// Router for subscription routes
 pub fn router() -> Router<AppState> {
     // Customer routes
@@ -0,0 +1,90 @@
+//! Subscription Plan API Routes
+//!
+//! Plans customers can subscribe to; renewals are billed by the subscription
+//! billing engine (`[subscription_billing]`):
+//! - GET  /api/v1/subscription-plans                - Plans open for sign-up
+//! - POST /api/v1/subscription-plans/:id/subscribe  - Subscribe the authenticated customer
+//! - GET  /api/v1/admin/subscription-plans          - All plans (admin)
+//! - POST /api/v1/admin/subscription-plans          - Create a plan (admin)
+//! - GET  /api/v1/admin/subscription-plans/:id      - Plan details (admin)
+//! - PUT  /api/v1/admin/subscription-plans/:id      - Update a plan; existing subscriptions keep their terms (admin)
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::state::AppState;
+use rcommerce_core::models::Subscription;
+use rcommerce_core::subscriptions::{
+    CreateSubscriptionPlanRequest, SubscribeToPlanRequest, SubscriptionPlan, UpdateSubscriptionPlanRequest,
+};
+use rcommerce_core::Error;
+
+/// GET /api/v1/subscription-plans
+pub async fn list_active_plans(State(state): State<AppState>) -> Result<Json<Vec<SubscriptionPlan>>, Error> {
+    Ok(Json(state.subscription_plans.list_plans(true).await?))
+}
+
+/// POST /api/v1/subscription-plans/:id/subscribe
+pub async fn subscribe(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(plan_id): Path<Uuid>,
+    Json(request): Json<SubscribeToPlanRequest>,
+) -> Result<(StatusCode, Json<Subscription>), Error> {
+    let subscription = state
+        .subscription_plans
+        .subscribe(&state.subscription_service, plan_id, auth.customer_id, request)
+        .await?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+/// GET /api/v1/admin/subscription-plans
+pub async fn list_plans(State(state): State<AppState>) -> Result<Json<Vec<SubscriptionPlan>>, Error> {
+    Ok(Json(state.subscription_plans.list_plans(false).await?))
+}
+
+/// POST /api/v1/admin/subscription-plans
+pub async fn create_plan(
+    State(state): State<AppState>,
+    Json(request): Json<CreateSubscriptionPlanRequest>,
+) -> Result<(StatusCode, Json<SubscriptionPlan>), Error> {
+    let plan = state.subscription_plans.create_plan(request).await?;
+    Ok((StatusCode::CREATED, Json(plan)))
+}
+
+/// GET /api/v1/admin/subscription-plans/:id
+pub async fn get_plan(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SubscriptionPlan>, Error> {
+    Ok(Json(state.subscription_plans.get_plan(id).await?))
+}
+
+/// PUT /api/v1/admin/subscription-plans/:id
+pub async fn update_plan(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateSubscriptionPlanRequest>,
+) -> Result<Json<SubscriptionPlan>, Error> {
+    Ok(Json(state.subscription_plans.update_plan(id, request).await?))
+}
+
+/// Router for customer plan routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/subscription-plans", get(list_active_plans))
+        .route("/subscription-plans/:id/subscribe", post(subscribe))
+}
+
+/// Router for plan management (mounted behind admin auth)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/subscription-plans", get(list_plans).post(create_plan))
+        .route("/admin/subscription-plans/:id", get(get_plan).put(update_plan))
+}
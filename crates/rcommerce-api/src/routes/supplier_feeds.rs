@@ -0,0 +1,160 @@
+//! Supplier Feed API Routes
+//!
+//! Supplier stock and price feeds, and the dropship orders paid orders of
+//! the suppliers' products are routed into (`[supplier_feeds]`):
+//! - GET    /api/v1/admin/suppliers/:id/feed         - Get the supplier's feed, with its webhook secret and last sync
+//! - PUT    /api/v1/admin/suppliers/:id/feed         - Create or replace the feed (`rotate_secret` for a new secret)
+//! - DELETE /api/v1/admin/suppliers/:id/feed         - Remove the feed
+//! - POST   /api/v1/admin/suppliers/:id/feed/sync    - Pull the feed from its source URL now
+//! - POST   /api/v1/admin/suppliers/:id/feed/upload  - Apply a feed file sent as the request body
+//! - GET    /api/v1/admin/dropship-orders            - Dropship orders (`?status=failed&supplier_id=...`)
+//! - POST   /api/v1/admin/dropship-orders/route      - Route paid orders and send dropship orders now
+//! - GET    /api/v1/admin/dropship-orders/:id        - Get a dropship order with its lines
+//! - POST   /api/v1/admin/dropship-orders/:id/retry  - Send a pending or failed dropship order again
+//!
+//! Suppliers push feeds to the public route, signed with the feed's
+//! webhook secret (`X-Feed-Signature: sha256=<hex HMAC-SHA256 of the body>`):
+//! - POST   /api/v1/supplier-feeds/:supplier_id
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::inventory::supplier_feed::{MAX_FEED_BYTES, MAX_LIST_LIMIT, SIGNATURE_HEADER};
+use rcommerce_core::inventory::{
+    DropshipOrder, DropshipOrderDetail, DropshipReport, DropshipStatus, FeedSyncReport, SaveSupplierFeedRequest,
+    SupplierFeed,
+};
+use rcommerce_core::Error;
+
+/// Query parameters for listing dropship orders
+#[derive(Debug, Deserialize)]
+pub struct ListDropshipOrdersQuery {
+    pub status: Option<DropshipStatus>,
+    pub supplier_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/admin/suppliers/:id/feed
+pub async fn get_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+) -> Result<Json<SupplierFeed>, Error> {
+    Ok(Json(state.supplier_feeds.get_feed(supplier_id).await?))
+}
+
+/// PUT /api/v1/admin/suppliers/:id/feed
+pub async fn save_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+    Json(request): Json<SaveSupplierFeedRequest>,
+) -> Result<Json<SupplierFeed>, Error> {
+    Ok(Json(state.supplier_feeds.save_feed(supplier_id, request).await?))
+}
+
+/// DELETE /api/v1/admin/suppliers/:id/feed
+pub async fn delete_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.supplier_feeds.delete_feed(supplier_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/v1/admin/suppliers/:id/feed/sync
+pub async fn sync_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+) -> Result<Json<FeedSyncReport>, Error> {
+    Ok(Json(state.supplier_feeds.sync(supplier_id).await?))
+}
+
+/// POST /api/v1/admin/suppliers/:id/feed/upload
+pub async fn upload_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+    body: Bytes,
+) -> Result<Json<FeedSyncReport>, Error> {
+    Ok(Json(state.supplier_feeds.upload(supplier_id, &body).await?))
+}
+
+/// GET /api/v1/admin/dropship-orders
+pub async fn list_dropship_orders(
+    State(state): State<AppState>,
+    Query(query): Query<ListDropshipOrdersQuery>,
+) -> Result<Json<Vec<DropshipOrder>>, Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_LIST_LIMIT);
+    Ok(Json(
+        state
+            .supplier_feeds
+            .list_dropship_orders(query.status, query.supplier_id, limit)
+            .await?,
+    ))
+}
+
+/// POST /api/v1/admin/dropship-orders/route
+pub async fn route_dropship_orders(
+    State(state): State<AppState>,
+) -> Result<Json<DropshipReport>, Error> {
+    Ok(Json(state.supplier_feeds.process_dropship().await?))
+}
+
+/// GET /api/v1/admin/dropship-orders/:id
+pub async fn get_dropship_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DropshipOrderDetail>, Error> {
+    Ok(Json(state.supplier_feeds.get_dropship_order(id).await?))
+}
+
+/// POST /api/v1/admin/dropship-orders/:id/retry
+pub async fn retry_dropship_order(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DropshipOrderDetail>, Error> {
+    Ok(Json(state.supplier_feeds.retry(id).await?))
+}
+
+/// POST /api/v1/supplier-feeds/:supplier_id
+pub async fn push_feed(
+    State(state): State<AppState>,
+    Path(supplier_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<FeedSyncReport>, Error> {
+    let signature = headers.get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    Ok(Json(state.supplier_feeds.ingest_signed(supplier_id, &body, signature).await?))
+}
+
+/// Admin router for supplier feeds and dropship orders
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/suppliers/:id/feed",
+            get(get_feed).put(save_feed).delete(delete_feed),
+        )
+        .route("/admin/suppliers/:id/feed/sync", post(sync_feed))
+        .route(
+            "/admin/suppliers/:id/feed/upload",
+            post(upload_feed).layer(DefaultBodyLimit::max(MAX_FEED_BYTES)),
+        )
+        .route("/admin/dropship-orders", get(list_dropship_orders))
+        .route("/admin/dropship-orders/route", post(route_dropship_orders))
+        .route("/admin/dropship-orders/:id", get(get_dropship_order))
+        .route("/admin/dropship-orders/:id/retry", post(retry_dropship_order))
+}
+
+/// Public router for feeds pushed by suppliers (the signature authenticates them)
+pub fn public_router() -> Router<AppState> {
+    Router::new().route(
+        "/supplier-feeds/:supplier_id",
+        post(push_feed).layer(DefaultBodyLimit::max(MAX_FEED_BYTES)),
+    )
+}
@@ -0,0 +1,65 @@
+//! Shipment Tracking Routes
+//!
+//! Aggregators push tracking updates to a public webhook authenticated by
+//! the token in its URL (`shipping.tracking.webhook_token`); shipments of
+//! other carriers are polled by the `tracking_poll` job:
+//! - POST /api/v1/shipping/tracking/:provider?token=...   - Tracking webhook of easypost or shipstation
+//! - POST /api/v1/admin/shipping/tracking/webhooks         - Register the webhook with the aggregators
+//! - POST /api/v1/admin/shipments/:id/tracking/refresh     - Ask the carrier for a shipment's tracking now
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::shipping::{TrackingInfo, TrackingWebhookResult};
+use rcommerce_core::Error;
+
+/// Query parameters of the webhook URL
+#[derive(Debug, Deserialize)]
+pub struct TrackingWebhookQuery {
+    #[serde(default)]
+    pub token: String,
+}
+
+/// POST /api/v1/shipping/tracking/:provider
+pub async fn tracking_webhook(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<TrackingWebhookQuery>,
+    body: Bytes,
+) -> Result<Json<TrackingWebhookResult>, Error> {
+    Ok(Json(state.tracking.handle_webhook(&provider, &query.token, &body).await?))
+}
+
+/// POST /api/v1/admin/shipping/tracking/webhooks
+pub async fn register_webhooks(State(state): State<AppState>) -> Result<Json<serde_json::Value>, Error> {
+    let registrations = state.tracking.register_webhooks().await?;
+    Ok(Json(json!({ "webhooks": registrations })))
+}
+
+/// POST /api/v1/admin/shipments/:id/tracking/refresh
+pub async fn refresh_tracking(
+    State(state): State<AppState>,
+    Path(shipment_id): Path<Uuid>,
+) -> Result<Json<TrackingInfo>, Error> {
+    Ok(Json(state.tracking.refresh(shipment_id).await?))
+}
+
+/// Router for tracking webhooks (public)
+pub fn router() -> Router<AppState> {
+    Router::new().route("/shipping/tracking/:provider", post(tracking_webhook))
+}
+
+/// Router for tracking admin routes
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/shipping/tracking/webhooks", post(register_webhooks))
+        .route("/admin/shipments/:id/tracking/refresh", post(refresh_tracking))
+}
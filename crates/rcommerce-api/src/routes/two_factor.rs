@@ -0,0 +1,202 @@
+//! Two-Factor Authentication API Routes
+//!
+//! TOTP 2FA of the signed-in account (logins then finish at
+//! `POST /api/v1/auth/2fa/verify`, see the auth routes):
+//! - GET    /api/v1/auth/2fa                    - Whether 2FA is enabled, backup codes left
+//! - POST   /api/v1/auth/2fa/enroll             - New secret and provisioning URI for the app
+//! - POST   /api/v1/auth/2fa/confirm            - Enable with a first code; returns backup codes
+//! - POST   /api/v1/auth/2fa/disable            - Disable with a code or backup code
+//! - POST   /api/v1/auth/2fa/backup-codes       - Replace the backup codes
+//!
+//! And for staff with `users:admin`:
+//! - DELETE /api/v1/admin/customers/:id/2fa     - Reset an account's 2FA, e.g. after a lost phone
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::middleware::JwtAuth;
+use crate::openapi::ErrorResponse;
+use crate::routes::auth::end_all_sessions;
+use crate::state::AppState;
+use rcommerce_core::models::{TwoFactorProof, TwoFactorSetup, TwoFactorStatus};
+use rcommerce_core::Error;
+
+/// First code from the authenticator app
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ConfirmTwoFactorRequest {
+    pub code: String,
+}
+
+/// A current code, or an unused backup code
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct TwoFactorProofRequest {
+    pub code: Option<String>,
+    pub backup_code: Option<String>,
+}
+
+impl From<TwoFactorProofRequest> for TwoFactorProof {
+    fn from(request: TwoFactorProofRequest) -> Self {
+        TwoFactorProof { code: request.code, backup_code: request.backup_code }
+    }
+}
+
+/// One-time backup codes; they are not shown again
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BackupCodesResponse {
+    pub backup_codes: Vec<String>,
+}
+
+/// Two-factor state of the signed-in account
+#[utoipa::path(
+    get,
+    path = "/auth/2fa",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Two-factor state", body = TwoFactorStatus),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_two_factor(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<TwoFactorStatus>, Error> {
+    Ok(Json(state.two_factor.status(auth.customer_id).await?))
+}
+
+/// Start enrollment
+///
+/// Returns a new secret for the authenticator app, usually shown as a QR
+/// code of `provisioning_uri`. 2FA is not enabled until a code from the app
+/// is sent to `/auth/2fa/confirm`; enrolling again replaces a pending secret.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/enroll",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Secret and provisioning URI", body = TwoFactorSetup),
+        (status = 400, description = "2FA is already enabled", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn enroll_two_factor(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+) -> Result<Json<TwoFactorSetup>, Error> {
+    Ok(Json(state.two_factor.begin_enrollment(auth.customer_id, &auth.email).await?))
+}
+
+/// Enable 2FA with a first code from the app
+///
+/// Returns the backup codes, and signs out every device: later logins need
+/// a code.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/confirm",
+    tag = "auth",
+    request_body = ConfirmTwoFactorRequest,
+    responses(
+        (status = 200, description = "2FA enabled", body = BackupCodesResponse),
+        (status = 400, description = "Invalid code, or no enrollment pending", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn confirm_two_factor(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(payload): Json<ConfirmTwoFactorRequest>,
+) -> Result<Json<BackupCodesResponse>, Error> {
+    let backup_codes = state
+        .two_factor
+        .confirm_enrollment(auth.customer_id, &payload.code, Utc::now())
+        .await?;
+    if let Err(e) = end_all_sessions(&state, auth.customer_id, "two-factor enabled").await {
+        tracing::warn!("Failed to end sessions for customer {}: {}", auth.customer_id, e);
+    }
+    Ok(Json(BackupCodesResponse { backup_codes }))
+}
+
+/// Disable 2FA
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/disable",
+    tag = "auth",
+    request_body = TwoFactorProofRequest,
+    responses(
+        (status = 204, description = "2FA disabled"),
+        (status = 401, description = "Invalid code", body = ErrorResponse),
+        (status = 429, description = "Too many wrong codes", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(payload): Json<TwoFactorProofRequest>,
+) -> Result<StatusCode, Error> {
+    state.two_factor.disable(auth.customer_id, &payload.into(), Utc::now()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace the backup codes, e.g. when few are left
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/backup-codes",
+    tag = "auth",
+    request_body = TwoFactorProofRequest,
+    responses(
+        (status = 200, description = "New backup codes; the old ones no longer work", body = BackupCodesResponse),
+        (status = 401, description = "Invalid code", body = ErrorResponse),
+        (status = 429, description = "Too many wrong codes", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn regenerate_backup_codes(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Json(payload): Json<TwoFactorProofRequest>,
+) -> Result<Json<BackupCodesResponse>, Error> {
+    let backup_codes = state
+        .two_factor
+        .regenerate_backup_codes(auth.customer_id, &payload.into(), Utc::now())
+        .await?;
+    Ok(Json(BackupCodesResponse { backup_codes }))
+}
+
+/// DELETE /api/v1/admin/customers/:id/2fa
+///
+/// For an account that lost its authenticator and backup codes. Ends the
+/// account's sessions too.
+pub async fn reset_two_factor(
+    State(state): State<AppState>,
+    Extension(auth): Extension<JwtAuth>,
+    Path(customer_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    state.two_factor.reset(customer_id).await?;
+    tracing::warn!("Two-factor authentication of customer {} reset by {}", customer_id, auth.customer_id);
+    if let Err(e) = end_all_sessions(&state, customer_id, "two-factor reset").await {
+        tracing::warn!("Failed to end sessions for customer {}: {}", customer_id, e);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Router for the signed-in account's 2FA
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/2fa", get(get_two_factor))
+        .route("/auth/2fa/enroll", post(enroll_two_factor))
+        .route("/auth/2fa/confirm", post(confirm_two_factor))
+        .route("/auth/2fa/disable", post(disable_two_factor))
+        .route("/auth/2fa/backup-codes", post(regenerate_backup_codes))
+}
+
+/// Router for resetting accounts' 2FA
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route("/admin/customers/:id/2fa", delete(reset_two_factor))
+}
@@ -0,0 +1,59 @@
+//! Unsubscribe API Routes
+//!
+//! Public endpoints behind the signed links in marketing emails. The token
+//! only covers unsubscribing from the link's category; changing any other
+//! preference needs the customer to sign in:
+//! - GET  /api/v1/unsubscribe/:token  - What the link unsubscribes from, and whether it still applies
+//! - POST /api/v1/unsubscribe/:token  - Unsubscribe (one-click, RFC 8058 `List-Unsubscribe-Post`)
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::state::AppState;
+use rcommerce_core::notification::NotificationCategory;
+use rcommerce_core::Error;
+
+/// Unsubscribe link response
+#[derive(Debug, Serialize)]
+pub struct UnsubscribeResponse {
+    pub category: NotificationCategory,
+    /// Whether the customer still gets notifications of the category
+    pub subscribed: bool,
+}
+
+/// GET /api/v1/unsubscribe/:token
+pub async fn get_unsubscribe(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<UnsubscribeResponse>, Error> {
+    let token = state.unsubscribe.verify(&token, Utc::now())?;
+    let preferences = state.accounts.notification_preferences(token.customer_id).await?;
+    Ok(Json(UnsubscribeResponse {
+        category: token.category,
+        subscribed: token.category.enabled_in(&preferences),
+    }))
+}
+
+/// POST /api/v1/unsubscribe/:token
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<UnsubscribeResponse>, Error> {
+    let token = state.unsubscribe.verify(&token, Utc::now())?;
+    let preferences = state.accounts.unsubscribe(token.customer_id, token.category).await?;
+    tracing::info!("Customer {} unsubscribed from {} notifications", token.customer_id, token.category.as_str());
+    Ok(Json(UnsubscribeResponse {
+        category: token.category,
+        subscribed: token.category.enabled_in(&preferences),
+    }))
+}
+
+/// Router for unsubscribe links (public; the signed token authenticates)
+pub fn router() -> Router<AppState> {
+    Router::new().route("/unsubscribe/:token", get(get_unsubscribe).post(unsubscribe))
+}
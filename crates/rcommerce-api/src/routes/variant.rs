@@ -0,0 +1,107 @@
+//! Product Variant API Routes
+//!
+//! Variants of variable products, defined by option values (Size = "M"):
+//! - GET    /api/v1/products/:id/variants             - Variants with their option values
+//! - GET    /api/v1/products/:id/variants/:variant_id - Variant details
+//! - GET    /api/v1/products/:id/options              - Option names with the values in use
+//! - POST   /api/v1/products/:id/variants             - Add a variant (admin)
+//! - PUT    /api/v1/products/:id/variants/:variant_id - Update a variant (admin)
+//! - DELETE /api/v1/products/:id/variants/:variant_id - Delete a variant (admin)
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{CreateVariantRequest, ProductOptionSet, ProductVariantWithOptions, UpdateVariantRequest};
+use rcommerce_core::Error;
+
+/// GET /api/v1/products/:id/variants
+pub async fn list_variants(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<Vec<ProductVariantWithOptions>>, Error> {
+    Ok(Json(state.product_service.list_variants(product_id).await?))
+}
+
+/// GET /api/v1/products/:id/variants/:variant_id
+pub async fn get_variant(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ProductVariantWithOptions>, Error> {
+    state
+        .product_service
+        .get_variant(product_id, variant_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| Error::not_found("Variant not found"))
+}
+
+/// GET /api/v1/products/:id/options
+pub async fn list_options(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<Vec<ProductOptionSet>>, Error> {
+    Ok(Json(state.product_service.list_options(product_id).await?))
+}
+
+/// POST /api/v1/products/:id/variants
+pub async fn create_variant(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<CreateVariantRequest>,
+) -> Result<(StatusCode, Json<ProductVariantWithOptions>), Error> {
+    let variant = state.product_service.create_variant(product_id, request).await?;
+    Ok((StatusCode::CREATED, Json(variant)))
+}
+
+/// PUT /api/v1/products/:id/variants/:variant_id
+pub async fn update_variant(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateVariantRequest>,
+) -> Result<Json<ProductVariantWithOptions>, Error> {
+    Ok(Json(state.product_service.update_variant(product_id, variant_id, request).await?))
+}
+
+/// DELETE /api/v1/products/:id/variants/:variant_id
+pub async fn delete_variant(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    if state.product_service.delete_variant(product_id, variant_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::not_found("Variant not found"))
+    }
+}
+
+/// Router for variant reads
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/products/:id/variants", get(list_variants))
+        .route("/products/:id/variants/:variant_id", get(get_variant))
+        .route("/products/:id/options", get(list_options))
+}
+
+/// Router for variant management (mounted behind admin auth)
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/products/:id/variants", post(create_variant))
+        .route("/products/:id/variants/:variant_id", put(update_variant).delete(delete_variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_and_admin_routers_merge() {
+        // Same paths with different methods, mounted under different auth layers
+        let _ = Router::<AppState>::new().merge(router()).merge(admin_router());
+    }
+}
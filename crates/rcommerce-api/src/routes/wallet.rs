@@ -0,0 +1,143 @@
+//! Wallet Payment API Routes
+//!
+//! Storefront setup for Apple Pay and Google Pay (`[payment.wallets]`).
+//! Wallet tokens themselves are paid with `POST /api/v1/payments` and
+//! `{"type": "digital_wallet", ...}` payment method data:
+//! - GET  /api/v1/payments/wallets                         - Wallet settings for the payment sheets
+//! - POST /api/v1/payments/apple-pay/session               - Apple Pay merchant session (onvalidatemerchant)
+//! - POST /api/v1/admin/payments/apple-pay/domains         - Register a storefront domain with a gateway
+//! - GET  /.well-known/apple-developer-merchantid-domain-association - Apple Pay domain verification file
+
+use axum::{extract::State, routing::{get, post}, Json, Router};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::state::AppState;
+use rcommerce_core::Error;
+
+/// Wallet settings the storefront needs to open a payment sheet
+#[derive(Debug, Serialize)]
+pub struct WalletSettingsResponse {
+    pub apple_pay: ApplePaySettings,
+    pub google_pay: GooglePaySettings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplePaySettings {
+    pub enabled: bool,
+    pub merchant_id: Option<String>,
+    pub display_name: String,
+    /// Whether merchant sessions come from `/payments/apple-pay/session`
+    /// rather than the gateway's JS SDK
+    pub merchant_validation: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GooglePaySettings {
+    pub enabled: bool,
+    pub merchant_id: Option<String>,
+    pub merchant_name: Option<String>,
+}
+
+/// Request for an Apple Pay merchant session
+#[derive(Debug, Deserialize)]
+pub struct ApplePaySessionRequest {
+    /// `validationURL` from the `onvalidatemerchant` event
+    pub validation_url: String,
+    /// Domain the payment sheet was opened on
+    pub domain: String,
+}
+
+/// Request to register an Apple Pay domain with a gateway
+#[derive(Debug, Deserialize)]
+pub struct RegisterApplePayDomainRequest {
+    /// Defaults to `payment.default_gateway`
+    pub gateway_id: Option<String>,
+    pub domain: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterApplePayDomainResponse {
+    pub gateway_id: String,
+    pub domain: String,
+}
+
+/// GET /api/v1/payments/wallets
+pub async fn get_wallet_settings(State(state): State<AppState>) -> Json<WalletSettingsResponse> {
+    let apple_pay = &state.wallets.apple_pay;
+    let google_pay = &state.wallets.google_pay;
+    Json(WalletSettingsResponse {
+        apple_pay: ApplePaySettings {
+            enabled: apple_pay.enabled,
+            merchant_id: apple_pay.merchant_id.clone(),
+            display_name: apple_pay.display_name.clone(),
+            merchant_validation: state.apple_pay.is_some(),
+        },
+        google_pay: GooglePaySettings {
+            enabled: google_pay.enabled,
+            merchant_id: google_pay.merchant_id.clone(),
+            merchant_name: google_pay.merchant_name.clone(),
+        },
+    })
+}
+
+/// POST /api/v1/payments/apple-pay/session
+pub async fn create_apple_pay_session(
+    State(state): State<AppState>,
+    Json(request): Json<ApplePaySessionRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let validator = state
+        .apple_pay
+        .as_ref()
+        .ok_or_else(|| Error::validation("Apple Pay merchant validation is not configured"))?;
+    let session = validator.create_session(&request.validation_url, &request.domain).await?;
+    Ok(Json(session))
+}
+
+/// POST /api/v1/admin/payments/apple-pay/domains
+pub async fn register_apple_pay_domain(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterApplePayDomainRequest>,
+) -> Result<Json<RegisterApplePayDomainResponse>, Error> {
+    let domain = request.domain.trim().to_ascii_lowercase();
+    if domain.is_empty() || domain.contains(['/', ':', ' ']) {
+        return Err(Error::validation("domain must be a host name, e.g. shop.example.com"));
+    }
+
+    let gateway_id = request
+        .gateway_id
+        .unwrap_or_else(|| state.payment_service.default_gateway().to_string());
+    let gateway = state
+        .payment_service
+        .get_gateway(Some(&gateway_id))
+        .ok_or_else(|| Error::validation(format!("Gateway '{}' not found", gateway_id)))?;
+    gateway.register_apple_pay_domain(&domain).await?;
+
+    info!("Registered Apple Pay domain {} with {}", domain, gateway_id);
+    Ok(Json(RegisterApplePayDomainResponse { gateway_id, domain }))
+}
+
+/// GET /.well-known/apple-developer-merchantid-domain-association
+pub async fn domain_association(State(state): State<AppState>) -> Result<String, Error> {
+    let apple_pay = &state.wallets.apple_pay;
+    let path = apple_pay
+        .domain_association_file
+        .as_ref()
+        .filter(|_| apple_pay.enabled)
+        .ok_or_else(|| Error::not_found("Apple Pay domain association file not configured"))?;
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to read Apple Pay domain association file: {}", e)))
+}
+
+/// Router for storefront wallet setup
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/payments/wallets", get(get_wallet_settings))
+        .route("/payments/apple-pay/session", post(create_apple_pay_session))
+}
+
+/// Admin router for wallet domain registration
+pub fn admin_router() -> Router<AppState> {
+    Router::new().route("/admin/payments/apple-pay/domains", post(register_apple_pay_domain))
+}
@@ -3,7 +3,16 @@
 //! Provides endpoints for managing webhooks including:
 //! - Creating, updating, and deleting webhooks
 //! - Testing webhook deliveries
+//! - Previewing an endpoint's payload template and headers
 //! - Viewing delivery history
+//!
+//! Endpoints can set a Handlebars `payload_template` and custom `headers`
+//! to reshape the canonical event payload (see
+//! `rcommerce_core::services::webhook_transform`). The signature covers the
+//! transformed body; delivery history keeps the canonical payload.
+//!
+//! Signing secrets are sealed with the `[secrets]` master key when one is
+//! configured, and opened only to sign a delivery.
 
 use axum::{
     extract::{Path, State, Query},
@@ -13,10 +22,16 @@ use axum::{
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 use sqlx::Row;
+use sqlx::postgres::PgRow;
 
 use crate::state::AppState;
+use rcommerce_core::services::{sign_webhook_body, WebhookEnvelope, WebhookTransform};
+
+/// Columns selected for a WebhookResponse
+const WEBHOOK_COLUMNS: &str = "id, name, url, events, is_active, payload_template, headers, last_triggered_at, created_at";
 
 /// Webhook response
 #[derive(Debug, Serialize)]
@@ -26,6 +41,8 @@ pub struct WebhookResponse {
     pub url: String,
     pub events: Vec<String>,
     pub is_active: bool,
+    pub payload_template: Option<String>,
+    pub headers: HashMap<String, String>,
     pub last_triggered_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
@@ -37,6 +54,11 @@ pub struct CreateWebhookRequest {
     pub url: String,
     pub events: Vec<String>,
     pub secret: Option<String>,
+    /// Handlebars template for the request body (canonical JSON when unset)
+    pub payload_template: Option<String>,
+    /// Extra request headers; values may be templates
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
 }
 
 /// Update webhook request
@@ -46,6 +68,10 @@ pub struct UpdateWebhookRequest {
     pub url: Option<String>,
     pub events: Option<Vec<String>>,
     pub is_active: Option<bool>,
+    /// An empty string removes the template
+    pub payload_template: Option<String>,
+    /// Replaces all custom headers
+    pub headers: Option<HashMap<String, String>>,
 }
 
 /// Test webhook request
@@ -65,6 +91,13 @@ pub struct WebhookDeliveryResponse {
     pub created_at: DateTime<Utc>,
 }
 
+/// Preview webhook request
+#[derive(Debug, Deserialize)]
+pub struct PreviewWebhookRequest {
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
 /// List webhooks query params
 #[derive(Debug, Deserialize)]
 pub struct ListWebhooksQuery {
@@ -73,6 +106,41 @@ pub struct ListWebhooksQuery {
     pub per_page: Option<i64>,
 }
 
+fn webhook_response(row: &PgRow) -> WebhookResponse {
+    WebhookResponse {
+        id: row.try_get::<Uuid, _>("id").map(|id| id.to_string()).unwrap_or_default(),
+        name: row.try_get("name").unwrap_or_default(),
+        url: row.try_get("url").unwrap_or_default(),
+        events: row.try_get::<Vec<String>, _>("events").unwrap_or_default(),
+        is_active: row.try_get("is_active").unwrap_or(true),
+        payload_template: row.try_get("payload_template").ok().flatten(),
+        headers: row
+            .try_get::<sqlx::types::Json<HashMap<String, String>>, _>("headers")
+            .map(|h| h.0)
+            .unwrap_or_default(),
+        last_triggered_at: row.try_get("last_triggered_at").ok(),
+        created_at: row.try_get("created_at").unwrap_or(Utc::now()),
+    }
+}
+
+/// Load an endpoint's transform
+async fn find_transform(pool: &sqlx::PgPool, id: Uuid) -> Result<WebhookTransform, StatusCode> {
+    let row = sqlx::query("SELECT payload_template, headers FROM webhooks WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(WebhookTransform {
+        payload_template: row.try_get("payload_template").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        headers: row
+            .try_get::<sqlx::types::Json<HashMap<String, String>>, _>("headers")
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .0,
+    })
+}
+
 /// List all webhooks
 pub async fn list_webhooks(
     State(state): State<AppState>,
@@ -84,28 +152,17 @@ pub async fn list_webhooks(
     let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
     let offset = (page - 1) * per_page;
     
-    let rows = sqlx::query(
-        "SELECT id, name, url, events, is_active, last_triggered_at, created_at FROM webhooks ORDER BY created_at DESC LIMIT $1 OFFSET $2"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM webhooks ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+        WEBHOOK_COLUMNS
+    ))
     .bind(per_page)
     .bind(offset)
     .fetch_all(pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let webhooks: Vec<WebhookResponse> = rows.iter().map(|r| {
-        WebhookResponse {
-            id: r.try_get::<Uuid, _>("id").map(|id| id.to_string()).unwrap_or_default(),
-            name: r.try_get("name").unwrap_or_default(),
-            url: r.try_get("url").unwrap_or_default(),
-            events: r.try_get::<Vec<String>, _>("events").unwrap_or_default(),
-            is_active: r.try_get("is_active").unwrap_or(true),
-            last_triggered_at: r.try_get("last_triggered_at").ok(),
-            created_at: r.try_get("created_at").unwrap_or(Utc::now()),
-        }
-    }).collect();
-    
-    Ok(Json(webhooks))
+    Ok(Json(rows.iter().map(webhook_response).collect()))
 }
 
 /// Get a single webhook
@@ -115,26 +172,14 @@ pub async fn get_webhook(
 ) -> Result<Json<WebhookResponse>, StatusCode> {
     let pool = state.db.pool();
     
-    let row = sqlx::query(
-        "SELECT id, name, url, events, is_active, last_triggered_at, created_at FROM webhooks WHERE id = $1"
-    )
+    let row = sqlx::query(&format!("SELECT {} FROM webhooks WHERE id = $1", WEBHOOK_COLUMNS))
     .bind(id)
     .fetch_optional(pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::NOT_FOUND)?;
     
-    let webhook = WebhookResponse {
-        id: row.try_get::<Uuid, _>("id").map(|id| id.to_string()).unwrap_or_default(),
-        name: row.try_get("name").unwrap_or_default(),
-        url: row.try_get("url").unwrap_or_default(),
-        events: row.try_get::<Vec<String>, _>("events").unwrap_or_default(),
-        is_active: row.try_get("is_active").unwrap_or(true),
-        last_triggered_at: row.try_get("last_triggered_at").ok(),
-        created_at: row.try_get("created_at").unwrap_or(Utc::now()),
-    };
-    
-    Ok(Json(webhook))
+    Ok(Json(webhook_response(&row)))
 }
 
 /// Create a new webhook
@@ -154,6 +199,12 @@ pub async fn create_webhook(
         return Err(StatusCode::BAD_REQUEST);
     }
     
+    let transform = WebhookTransform {
+        payload_template: request.payload_template.filter(|t| !t.trim().is_empty()),
+        headers: request.headers,
+    };
+    transform.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    
     // Generate secret if not provided
     let secret = request.secret.unwrap_or_else(|| {
         use rand::Rng;
@@ -162,35 +213,33 @@ pub async fn create_webhook(
         hex::encode(bytes)
     });
     
+    let sealed_secret = state.secrets.secrets().seal(&secret).map_err(|e| {
+        tracing::error!("Failed to seal webhook secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
     let id = Uuid::new_v4();
     
-    let row = sqlx::query(
+    let row = sqlx::query(&format!(
         r#"
-        INSERT INTO webhooks (id, name, url, secret, events, is_active, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, true, NOW(), NOW())
-        RETURNING id, name, url, events, is_active, last_triggered_at, created_at
-        "#
-    )
+        INSERT INTO webhooks (id, name, url, secret, events, is_active, payload_template, headers, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, true, $6, $7, NOW(), NOW())
+        RETURNING {}
+        "#,
+        WEBHOOK_COLUMNS
+    ))
     .bind(id)
     .bind(&request.name)
     .bind(&request.url)
-    .bind(&secret)
+    .bind(&sealed_secret)
     .bind(&request.events)
+    .bind(&transform.payload_template)
+    .bind(sqlx::types::Json(&transform.headers))
     .fetch_one(pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let webhook = WebhookResponse {
-        id: row.try_get::<Uuid, _>("id").map(|id| id.to_string()).unwrap_or_default(),
-        name: row.try_get("name").unwrap_or_default(),
-        url: row.try_get("url").unwrap_or_default(),
-        events: row.try_get::<Vec<String>, _>("events").unwrap_or_default(),
-        is_active: row.try_get("is_active").unwrap_or(true),
-        last_triggered_at: row.try_get("last_triggered_at").ok(),
-        created_at: row.try_get("created_at").unwrap_or(Utc::now()),
-    };
-    
-    Ok(Json(webhook))
+    Ok(Json(webhook_response(&row)))
 }
 
 /// Update a webhook
@@ -246,31 +295,40 @@ pub async fn update_webhook(
         updates.push("is_active");
     }
     
+    if request.payload_template.is_some() || request.headers.is_some() {
+        let current = find_transform(pool, id).await?;
+        let transform = WebhookTransform {
+            payload_template: match request.payload_template {
+                Some(template) if template.trim().is_empty() => None,
+                Some(template) => Some(template),
+                None => current.payload_template,
+            },
+            headers: request.headers.unwrap_or(current.headers),
+        };
+        transform.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+        
+        let _ = sqlx::query("UPDATE webhooks SET payload_template = $1, headers = $2, updated_at = NOW() WHERE id = $3")
+            .bind(&transform.payload_template)
+            .bind(sqlx::types::Json(&transform.headers))
+            .bind(id)
+            .execute(pool)
+            .await;
+        updates.push("transform");
+    }
+    
     if updates.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
     
     // Fetch updated webhook
-    let row = sqlx::query(
-        "SELECT id, name, url, events, is_active, last_triggered_at, created_at FROM webhooks WHERE id = $1"
-    )
+    let row = sqlx::query(&format!("SELECT {} FROM webhooks WHERE id = $1", WEBHOOK_COLUMNS))
     .bind(id)
     .fetch_optional(pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::NOT_FOUND)?;
     
-    let webhook = WebhookResponse {
-        id: row.try_get::<Uuid, _>("id").map(|id| id.to_string()).unwrap_or_default(),
-        name: row.try_get("name").unwrap_or_default(),
-        url: row.try_get("url").unwrap_or_default(),
-        events: row.try_get::<Vec<String>, _>("events").unwrap_or_default(),
-        is_active: row.try_get("is_active").unwrap_or(true),
-        last_triggered_at: row.try_get("last_triggered_at").ok(),
-        created_at: row.try_get("created_at").unwrap_or(Utc::now()),
-    };
-    
-    Ok(Json(webhook))
+    Ok(Json(webhook_response(&row)))
 }
 
 /// Delete a webhook
@@ -305,7 +363,7 @@ pub async fn test_webhook(
     let pool = state.db.pool();
     
     // Get webhook details
-    let row = sqlx::query("SELECT url, secret, events FROM webhooks WHERE id = $1 AND is_active = true")
+    let row = sqlx::query("SELECT url, secret, events, payload_template, headers FROM webhooks WHERE id = $1 AND is_active = true")
         .bind(id)
         .fetch_optional(pool)
         .await
@@ -314,7 +372,18 @@ pub async fn test_webhook(
     
     let url: String = row.try_get("url").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let secret: String = row.try_get("secret").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let secret = state.secrets.secrets().open(&secret).map_err(|e| {
+        tracing::error!("Failed to open secret of webhook {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
     let events: Vec<String> = row.try_get("events").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let transform = WebhookTransform {
+        payload_template: row.try_get("payload_template").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        headers: row
+            .try_get::<sqlx::types::Json<HashMap<String, String>>, _>("headers")
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .0,
+    };
     
     // Verify event type is in webhook's events
     if !events.contains(&request.event_type) {
@@ -323,29 +392,38 @@ pub async fn test_webhook(
     
     // Build test payload
     let payload = request.payload.unwrap_or_else(|| {
-        serde_json::json!({
-            "event": request.event_type,
-            "test": true,
-            "timestamp": Utc::now().to_rfc3339(),
-            "data": {
-                "message": "This is a test webhook delivery"
-            }
-        })
+        serde_json::to_value(WebhookEnvelope::test(request.event_type.clone())).unwrap_or_default()
     });
     
-    // Sign payload
-    let signature = sign_payload(&payload, &secret);
+    // Apply the endpoint's template and headers
+    let outgoing = match transform.apply(id, &request.event_type, &payload) {
+        Ok(outgoing) => outgoing,
+        Err(e) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "message": e.to_string()
+            })));
+        }
+    };
+    
+    // Sign the body as sent
+    let signature = sign_webhook_body(&outgoing.body, &secret);
     
     // Send test webhook
     let client = reqwest::Client::new();
     let start = std::time::Instant::now();
     
-    let response = client
+    let mut builder = client
         .post(&url)
-        .header("Content-Type", "application/json")
+        .header("Content-Type", &outgoing.content_type)
         .header("X-Webhook-Signature", signature)
-        .header("X-Webhook-Test", "true")
-        .json(&payload)
+        .header("X-Webhook-Test", "true");
+    for (name, value) in &outgoing.headers {
+        builder = builder.header(name, value);
+    }
+    
+    let response = builder
+        .body(outgoing.body)
         .timeout(std::time::Duration::from_secs(30))
         .send()
         .await;
@@ -412,6 +490,28 @@ pub async fn test_webhook(
     }
 }
 
+/// Render an endpoint's payload template and headers for an event without sending it
+pub async fn preview_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PreviewWebhookRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let transform = find_transform(state.db.pool(), id).await?;
+    
+    match transform.apply(id, &request.event_type, &request.payload) {
+        Ok(outgoing) => Ok(Json(serde_json::json!({
+            "success": true,
+            "content_type": outgoing.content_type,
+            "headers": outgoing.headers.into_iter().collect::<HashMap<_, _>>(),
+            "body": outgoing.body
+        }))),
+        Err(e) => Ok(Json(serde_json::json!({
+            "success": false,
+            "message": e.to_string()
+        }))),
+    }
+}
+
 /// Get webhook delivery history
 pub async fn get_webhook_deliveries(
     State(state): State<AppState>,
@@ -453,19 +553,32 @@ pub async fn get_webhook_deliveries(
     Ok(Json(deliveries))
 }
 
-/// Sign webhook payload with HMAC-SHA256
-fn sign_payload(payload: &serde_json::Value, secret: &str) -> String {
-    use hmac::{Hmac, Mac};
-    use sha2::Sha256;
-    
-    type HmacSha256 = Hmac<Sha256>;
-    
-    let payload_str = payload.to_string();
-    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
-        .expect("HMAC can take key of any size");
-    mac.update(payload_str.as_bytes());
-    let result = mac.finalize();
-    format!("sha256={}", hex::encode(result.into_bytes()))
+/// Seal plain-text webhook secrets and rewrap ones under previous master
+/// keys once at startup, when `[secrets] reencrypt_on_startup = true`; one
+/// starting instance at a time does it
+pub fn spawn_secret_reencryption(state: &AppState, config: &rcommerce_core::config::SecretsConfig) {
+    let secrets = state.secrets.clone();
+    if !config.reencrypt_on_startup || !secrets.secrets().is_enabled() {
+        return;
+    }
+    let locks = state.locks.clone();
+    tokio::spawn(async move {
+        let reencrypt = async {
+            match secrets.reencrypt(false).await {
+                Ok(report) if report.resealed > 0 || report.failed > 0 => tracing::info!(
+                    "Re-encrypted {} of {} stored secrets ({} could not be opened)",
+                    report.resealed,
+                    report.checked,
+                    report.failed
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Secret re-encryption failed: {}", e),
+            }
+        };
+        if let Err(e) = rcommerce_core::jobs::lock::run_once(locks.as_ref(), "secret_reencryption", reencrypt).await {
+            tracing::error!("Secret re-encryption lock failed: {}", e);
+        }
+    });
 }
 
 /// Create webhook router
@@ -474,5 +587,6 @@ pub fn router() -> Router<AppState> {
         .route("/webhooks", get(list_webhooks).post(create_webhook))
         .route("/webhooks/:id", get(get_webhook).put(update_webhook).delete(delete_webhook))
         .route("/webhooks/:id/test", post(test_webhook))
+        .route("/webhooks/:id/preview", post(preview_webhook))
         .route("/webhooks/:id/deliveries", get(get_webhook_deliveries))
 }
@@ -0,0 +1,98 @@
+//! Webhook Replay API Routes
+//!
+//! Admin-only endpoints for re-sending an endpoint's outbound webhook events
+//! after a consumer outage:
+//! - POST /api/v1/admin/webhooks/:id/replays                    - Start a replay (`"dry_run": true` only counts events)
+//! - GET  /api/v1/admin/webhooks/:id/replays                    - Recent replays for the endpoint
+//! - GET  /api/v1/admin/webhooks/:id/replays/:replay_id         - Replay progress
+//! - POST /api/v1/admin/webhooks/:id/replays/:replay_id/cancel  - Stop a replay between batches
+//!
+//! Replays run in the background at the requested rate. Replayed payloads
+//! carry a `replay` object and an `X-Webhook-Replay` header.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use rcommerce_core::models::{CreateWebhookReplayRequest, WebhookReplay};
+use rcommerce_core::repository::WebhookReplayRepository;
+use rcommerce_core::Error;
+
+/// Replays listed per endpoint
+const LIST_LIMIT: i64 = 50;
+
+/// POST /api/v1/admin/webhooks/:id/replays
+pub async fn create_replay(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+    Json(request): Json<CreateWebhookReplayRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), Error> {
+    let replays = state.webhook_replay.clone();
+
+    if request.dry_run {
+        let events = replays.preview(webhook_id, &request).await?;
+        return Ok((StatusCode::OK, Json(serde_json::json!({ "dry_run": true, "events": events }))));
+    }
+
+    let replay = replays.create(webhook_id, &request, Some("admin-api")).await?;
+    let replay_id = replay.id;
+    tokio::spawn(async move {
+        match replays.run(replay_id).await {
+            Ok(replay) => tracing::info!(
+                "Webhook replay {} {:?}: {} sent, {} failed",
+                replay.id, replay.status, replay.sent, replay.failed
+            ),
+            Err(e) => tracing::error!("Webhook replay {} failed: {}", replay_id, e),
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "replay": replay }))))
+}
+
+/// GET /api/v1/admin/webhooks/:id/replays
+pub async fn list_replays(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<Json<Vec<WebhookReplay>>, Error> {
+    Ok(Json(state.webhook_replay.repository().list_for_webhook(webhook_id, LIST_LIMIT).await?))
+}
+
+/// GET /api/v1/admin/webhooks/:id/replays/:replay_id
+pub async fn get_replay(
+    State(state): State<AppState>,
+    Path((webhook_id, replay_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<WebhookReplay>, Error> {
+    find_replay(&state, webhook_id, replay_id).await.map(Json)
+}
+
+/// POST /api/v1/admin/webhooks/:id/replays/:replay_id/cancel
+pub async fn cancel_replay(
+    State(state): State<AppState>,
+    Path((webhook_id, replay_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<WebhookReplay>, Error> {
+    find_replay(&state, webhook_id, replay_id).await?;
+    Ok(Json(state.webhook_replay.cancel(replay_id).await?))
+}
+
+async fn find_replay(state: &AppState, webhook_id: Uuid, replay_id: Uuid) -> Result<WebhookReplay, Error> {
+    state
+        .webhook_replay
+        .repository()
+        .find(replay_id)
+        .await?
+        .filter(|r| r.webhook_id == webhook_id)
+        .ok_or_else(|| Error::not_found("Webhook replay not found"))
+}
+
+/// Router for webhook replay routes
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/webhooks/:id/replays", get(list_replays).post(create_replay))
+        .route("/admin/webhooks/:id/replays/:replay_id", get(get_replay))
+        .route("/admin/webhooks/:id/replays/:replay_id/cancel", post(cancel_replay))
+}
@@ -0,0 +1,102 @@
+//! Recurring jobs run by the API server
+//!
+//! Registers the jobs this server can run and starts the scheduler (see
+//! `rcommerce_core::jobs::recurring`). With several instances, each job
+//! runs on whichever instance takes its lease first. `rcommerce worker`
+//! runs the same jobs without the HTTP server (see `crate::worker`).
+
+use std::sync::Arc;
+
+use crate::state::AppState;
+use rcommerce_core::analytics::AnalyticsRetentionJob;
+use rcommerce_core::automation::AutomationJob;
+use rcommerce_core::config::JobSchedulerConfig;
+use rcommerce_core::inventory::{DropshipJob, PurchaseOrderDispatchJob, ReorderJob, SupplierFeedJob};
+use rcommerce_core::jobs::{RecurringJob, RecurringScheduler};
+use rcommerce_core::marketplace::{adapters_from_config, MarketplaceSync, MarketplaceSyncJob};
+use rcommerce_core::observability::{AnomalyJob, MetricAlertJob};
+use rcommerce_core::order::BackorderJob;
+use rcommerce_core::payment::agnostic::CaptureMethod;
+use rcommerce_core::reports::{ReportJob, SalesViewRefreshJob};
+use rcommerce_core::services::{AccessLogRetentionJob, AuditRetentionJob, AuthorizationExpiryJob, PayoutReconciliationJob, SoftDeletePurgeJob};
+use rcommerce_core::repository::PostgresScheduledJobRepository;
+use rcommerce_core::shipping::TrackingPollJob;
+use rcommerce_core::tax::VatReverificationJob;
+
+/// Start the recurring job scheduler unless it is disabled
+pub fn spawn(state: &AppState, config: &JobSchedulerConfig) {
+    if !config.enabled {
+        tracing::info!("Job scheduler disabled on this instance; recurring jobs run on other instances");
+        return;
+    }
+    if let Some(scheduler) = build(state, config) {
+        Arc::new(scheduler).start();
+    }
+}
+
+/// The scheduler with every job this instance can run; None if there are none
+pub fn build(state: &AppState, config: &JobSchedulerConfig) -> Option<RecurringScheduler<PostgresScheduledJobRepository>> {
+    let repository = PostgresScheduledJobRepository::new(state.db.pool().clone());
+    let mut scheduler = RecurringScheduler::new(repository, config.clone());
+    if let Some(job) = crate::routes::subscription::billing_job(state) {
+        scheduler.register(job);
+    }
+    if let Some(job) = marketplace_job(state) {
+        scheduler.register(job);
+    }
+    scheduler.register(Arc::new(AutomationJob::new(state.automation.clone())));
+    scheduler.register(Arc::new(ReorderJob::new(state.purchasing.clone())));
+    scheduler.register(Arc::new(PurchaseOrderDispatchJob::new(state.po_dispatch.clone())));
+    scheduler.register(Arc::new(SupplierFeedJob::new(state.supplier_feeds.clone())));
+    scheduler.register(Arc::new(DropshipJob::new(state.supplier_feeds.clone())));
+    scheduler.register(Arc::new(BackorderJob::new(state.shipments.clone())));
+    scheduler.register(Arc::new(ReportJob::new(state.reports.clone())));
+    scheduler.register(Arc::new(SalesViewRefreshJob::new(state.sales.clone())));
+    if state.tracking.config().enabled {
+        scheduler.register(Arc::new(TrackingPollJob::new(state.tracking.clone())));
+    }
+    if state.analytics.config().enabled {
+        scheduler.register(Arc::new(AnalyticsRetentionJob::new(state.analytics.clone())));
+    }
+    if state.access_log.config().enabled {
+        scheduler.register(Arc::new(AccessLogRetentionJob::new(state.access_log.clone())));
+    }
+    if state.audit.config().enabled {
+        scheduler.register(Arc::new(AuditRetentionJob::new(state.audit.clone())));
+    }
+    if state.soft_deletes.config().retention_days > 0 {
+        scheduler.register(Arc::new(SoftDeletePurgeJob::new(state.soft_deletes.clone())));
+    }
+    if state.payment_captures.config().method == CaptureMethod::Manual {
+        scheduler.register(Arc::new(AuthorizationExpiryJob::new(state.payment_captures.clone())));
+    }
+    if state.payouts.config().enabled {
+        scheduler.register(Arc::new(PayoutReconciliationJob::new(state.payouts.clone())));
+    }
+    if state.tax_service.vat_config().validate_vat_ids {
+        scheduler.register(Arc::new(VatReverificationJob::new(state.tax_service.clone())));
+    }
+    if !state.metrics.config().alerts.rules.is_empty() {
+        scheduler.register(Arc::new(MetricAlertJob::new(state.metrics.clone())));
+    }
+    if state.metrics.config().anomalies.enabled {
+        scheduler.register(Arc::new(AnomalyJob::new(state.metrics.clone())));
+    }
+
+    if scheduler.job_names().is_empty() {
+        tracing::info!("No recurring jobs to schedule");
+        return None;
+    }
+    Some(scheduler)
+}
+
+/// The marketplace sync job, when a marketplace is configured
+fn marketplace_job(state: &AppState) -> Option<Arc<dyn RecurringJob>> {
+    let config = state.marketplaces.as_ref().clone();
+    let adapters = adapters_from_config(&config);
+    if adapters.is_empty() {
+        return None;
+    }
+    let sync = MarketplaceSync::new(state.marketplace.as_ref().clone(), adapters, config);
+    Some(Arc::new(MarketplaceSyncJob::new(sync)))
+}
@@ -1,27 +1,37 @@
 use axum::{
     middleware,
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use std::net::SocketAddr;
 use std::time::Duration;
+use tower_http::compression::{
+    predicate::{DefaultPredicate, Predicate, SizeAbove},
+    CompressionLayer,
+};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
 
-use crate::middleware::{admin_middleware, auth_middleware, security_headers_middleware};
+use crate::middleware::geoip::TrustedProxies;
+use crate::middleware::{access_log_middleware, admin_middleware, audit_middleware, auth_middleware, capture_middleware, conditional_get_middleware, geoip_middleware, idempotency_middleware, request_stats_middleware, response_cache_middleware, security_headers_middleware, storefront_key_middleware};
 
 use crate::state::{AppState, AppStateParams};
-use rcommerce_core::cache::RedisPool;
-use rcommerce_core::config::{CorsConfig, TlsConfig};
-use rcommerce_core::repository::{create_pool, CustomerRepository, Database, ProductRepository, PostgresApiKeyRepository, PostgresSubscriptionRepository, PgCouponRepository, PgCartRepository};
+use rcommerce_core::cache::{FlashSaleStore, RedisPool};
+use rcommerce_core::config::{AdminUiConfig, CorsConfig, ServerConfig, TlsConfig};
+use rcommerce_core::repository::{create_pool, create_replica_set, CustomerRepository, Database, ProductRepository, PostgresAddonRepository, PostgresApiKeyRepository, PostgresCheckoutFieldRepository, PostgresCustomsRepository, PostgresDeliveryRepository, PostgresFraudRepository, PostgresGiftCardRepository, PostgresPickupRepository, PostgresPurchaseLimitRepository, PostgresSubscriptionRepository, PgCouponRepository, PgCartRepository};
 use std::sync::Arc;
-use rcommerce_core::services::{AuthService, CustomerService, ProductService, CouponService, CartService, CheckoutService, CheckoutConfig, OrderService};
-use rcommerce_core::inventory::{InventoryService, InventoryConfig};
+use rcommerce_core::services::{AuthService, CustomerService, ProductService, CouponService, CartService, CheckoutService, CheckoutConfig, CheckoutFieldSchema, FraudScreen, OrderService, PurchaseLimiter};
+use rcommerce_core::inventory::InventoryService;
+use rcommerce_core::events::EventBus;
+use rcommerce_core::jobs::spawn_singleton;
 use rcommerce_core::order::lifecycle::OrderEventDispatcher;
-use rcommerce_core::tax::DefaultTaxService;
-use rcommerce_core::shipping::ShippingProviderFactory;
+use rcommerce_core::order::OrderNumbers;
+use rcommerce_core::secrets::SecretBox;
+use rcommerce_core::tax::{DefaultTaxService, HsCodeEstimator, ViesValidator};
+use rcommerce_core::shipping::{DeliveryScheduler, ShippingProviderFactory};
 use rcommerce_core::payment::agnostic::PaymentService;
+use rcommerce_core::payment::wallet::ApplePayMerchantValidator;
 use rcommerce_core::payment::gateways::stripe_agnostic::StripeAgnosticGateway;
 use rcommerce_core::payment::gateways::wechatpay_agnostic::WeChatPayAgnosticGateway;
 use rcommerce_core::payment::gateways::alipay_agnostic::AliPayAgnosticGateway;
@@ -51,9 +61,22 @@ async fn run_http_server(config: Config) -> Result<()> {
 
     // Initialize application state
     let app_state = create_app_state(&config).await?;
+    crate::routes::cache::spawn_startup_warmup(&app_state);
+    spawn_event_handlers(&app_state);
+    crate::routes::live::spawn_listener(&app_state);
+    crate::routes::in_app_notification::spawn_listener(&app_state);
+    crate::routes::notification_template::spawn_theme_reload(&app_state);
+    if config.scheduler.in_api_server {
+        crate::scheduler::spawn(&app_state, &config.scheduler);
+        spawn_background_tasks(&app_state, &config);
+    } else {
+        info!("Recurring jobs and background tasks run in `rcommerce worker` processes (scheduler.in_api_server = false)");
+    }
+    crate::middleware::request_stats::spawn_flush(&app_state);
+    crate::middleware::access_log::spawn_flush(&app_state);
 
     // Build router
-    let app = build_router(app_state, None, &config.server.cors);
+    let app = build_router(app_state, None, &config.server, &config.admin_ui);
 
     info!("R Commerce API server listening on http://{}", addr);
     log_routes(&config);
@@ -63,7 +86,7 @@ async fn run_http_server(config: Config) -> Result<()> {
         .await
         .map_err(|e| rcommerce_core::Error::Network(e.to_string()))?;
 
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .map_err(|e| rcommerce_core::Error::Network(e.to_string()))?;
 
@@ -106,9 +129,22 @@ async fn run_tls_server(config: Config) -> Result<()> {
 
     // Initialize application state
     let app_state = create_app_state(&config).await?;
+    crate::routes::cache::spawn_startup_warmup(&app_state);
+    spawn_event_handlers(&app_state);
+    crate::routes::live::spawn_listener(&app_state);
+    crate::routes::in_app_notification::spawn_listener(&app_state);
+    crate::routes::notification_template::spawn_theme_reload(&app_state);
+    if config.scheduler.in_api_server {
+        crate::scheduler::spawn(&app_state, &config.scheduler);
+        spawn_background_tasks(&app_state, &config);
+    } else {
+        info!("Recurring jobs and background tasks run in `rcommerce worker` processes (scheduler.in_api_server = false)");
+    }
+    crate::middleware::request_stats::spawn_flush(&app_state);
+    crate::middleware::access_log::spawn_flush(&app_state);
 
     // Build main API router (HTTPS)
-    let api_app = build_router(app_state.clone(), Some(config.tls.clone()), &config.server.cors);
+    let api_app = build_router(app_state.clone(), Some(config.tls.clone()), &config.server, &config.admin_ui);
 
     // Build HTTP challenge router (HTTP port 80)
     let http_app = build_http_challenge_router(app_state.clone());
@@ -171,6 +207,24 @@ async fn start_manual_tls_server(
 
     info!("Loading TLS certificates from {:?}", cert_file);
 
+    if let Some(client_auth) = tls_config.client_auth() {
+        let rustls_config = crate::tls::client_auth::server_config(cert_file, key_file, client_auth)?;
+        info!("Client certificates verified against {:?}", client_auth.ca_file);
+        info!("Starting HTTPS server with mutual TLS on {}", addr);
+
+        let acceptor = crate::tls::ClientAuthAcceptor::new(rustls_config);
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum_server::bind(addr)
+                .acceptor(acceptor)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+            {
+                error!("HTTPS server error: {}", e);
+            }
+        });
+        return Ok(handle);
+    }
+
     // Load certificates using axum-server's RustlsConfig
     let rustls_config = RustlsConfig::from_pem_file(cert_file, key_file)
         .await
@@ -181,7 +235,7 @@ async fn start_manual_tls_server(
 
     let handle = tokio::spawn(async move {
         if let Err(e) = axum_server::bind_rustls(addr, rustls_config)
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await
         {
             error!("HTTPS server error: {}", e);
@@ -251,7 +305,7 @@ async fn start_lets_encrypt_server(
     let handle = tokio::spawn(async move {
         if let Err(e) = axum_server::bind(addr)
             .acceptor(acceptor)
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await
         {
             error!("HTTPS server error: {}", e);
@@ -317,8 +371,69 @@ async fn handle_http_redirect(
     )
 }
 
+/// Subscribe the handlers of domain events (webhooks, customer notices) to
+/// the event bus, start relaying events between instances and publish the
+/// outbox
+pub(crate) fn spawn_event_handlers(app_state: &AppState) {
+    for handler in app_state.event_handlers.iter() {
+        app_state.events.subscribe(handler.clone());
+    }
+    app_state.events.spawn_relay();
+    if app_state.events.is_relayed() {
+        info!("Domain events are relayed to other instances through Redis");
+    }
+    let outbox = app_state.outbox.clone();
+    let pool = app_state.db.pool().clone();
+    tokio::spawn(async move { outbox.run(pool).await });
+}
+
+/// Background tasks that don't serve requests: order archiving, partition
+/// maintenance, gift card expiry, idempotency key, login session and event
+/// outbox purges, stock reservation expiry and secret re-encryption
+pub(crate) fn spawn_background_tasks(app_state: &AppState, config: &Config) {
+    crate::routes::order_archive::spawn_archiver(app_state);
+    crate::routes::partitions::spawn_maintenance(app_state);
+    crate::routes::gift_card::spawn_expiry(app_state);
+    crate::middleware::idempotency::spawn_purge(app_state);
+    spawn_outbox_purge(app_state);
+    spawn_reservation_expiry(app_state, config);
+    crate::routes::auth::spawn_session_purge(app_state);
+    crate::routes::webhook::spawn_secret_reencryption(app_state, &config.secrets);
+}
+
+/// Delete processed outbox events past their retention, hourly, on one instance
+fn spawn_outbox_purge(app_state: &AppState) {
+    let outbox = app_state.outbox.clone();
+    spawn_singleton(app_state.locks.clone(), "event_outbox_purge", std::time::Duration::from_secs(3600), move || {
+        let outbox = outbox.clone();
+        async move {
+            match outbox.purge().await {
+                Ok(purged) if purged > 0 => info!("Purged {} processed outbox events", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Event outbox purge failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Mark stock reservations of unpaid orders expired, every minute, on one
+/// instance; expired reservations stop holding stock even before this runs
+fn spawn_reservation_expiry(app_state: &AppState, config: &Config) {
+    let inventory = Arc::new(InventoryService::new(app_state.db.clone(), config.inventory.clone()));
+    spawn_singleton(app_state.locks.clone(), "stock_reservation_expiry", std::time::Duration::from_secs(60), move || {
+        let inventory = inventory.clone();
+        async move {
+            match inventory.cleanup_expired_reservations().await {
+                Ok(expired) if expired > 0 => info!("Expired {} stock reservations", expired),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Stock reservation expiry failed: {}", e),
+            }
+        }
+    });
+}
+
 /// Create application state
-async fn create_app_state(config: &Config) -> Result<AppState> {
+pub(crate) async fn create_app_state(config: &Config) -> Result<AppState> {
     // Initialize database connection
     info!("Connecting to PostgreSQL database...");
     let pool = create_pool(
@@ -330,7 +445,7 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
         config.database.pool_size,
     )
     .await?;
-    let db = Database::new(pool);
+    let db = Database::new(pool).with_read_replicas(create_replica_set(&config.database)?);
 
     // Initialize repositories
     let product_repo = ProductRepository::new(db.clone());
@@ -345,11 +460,13 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
     let customer_service = CustomerService::new(customer_repo);
     let auth_service = AuthService::new(config.clone());
     let coupon_service = CouponService::new(coupon_repo.clone(), cart_repo.clone());
+    let purchase_limits = PurchaseLimiter::new(Arc::new(PostgresPurchaseLimitRepository::new(db.pool().clone())));
     let cart_service = CartService::new(
         cart_repo.clone(),
         coupon_repo.clone(),
         Arc::new(coupon_service.clone()),
-    );
+    )
+    .with_purchase_limits(purchase_limits.clone());
 
     // Initialize payment service with gateways
     let default_gateway = config.payment.default_gateway.clone();
@@ -455,25 +572,34 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
     // Initialize file upload service
     let file_upload_service = rcommerce_core::FileUploadService::from_config(config)?;
 
+    // Product image storage
+    let media_storage = rcommerce_core::media::media_storage(&config.media)?;
+    info!(
+        "Product images stored in {:?} storage (resizing: {})",
+        config.media.storage_type, config.media.image_processing.enabled
+    );
+
     // Initialize order service
-    let inventory_config = InventoryConfig {
-        low_stock_threshold: 20,
-        enable_restock_alerts: true,
-        enable_reservations: true,
-        reservation_timeout_minutes: 30,
-    };
-    let inventory_service = InventoryService::new(db.clone(), inventory_config);
-    let event_dispatcher = OrderEventDispatcher::new();
+    let inventory_service = InventoryService::new(db.clone(), config.inventory.clone());
+    // Create the domain event bus, relayed between instances through Redis
+    let mut events = EventBus::new(&config.events);
+    if let (Some(redis), true) = (&redis, config.events.redis_relay) {
+        events = events.with_redis(redis.clone(), config.events.redis_channel.clone());
+    }
+    let event_dispatcher = OrderEventDispatcher::new()
+        .with_pool(db.pool().clone())
+        .with_events(events.clone());
     let mock_gateway_for_orders = Box::new(MockPaymentGateway::new());
-    let order_service = Arc::new(OrderService::new(
-        db.clone(),
-        mock_gateway_for_orders,
-        inventory_service,
-        event_dispatcher,
-    ));
+    let order_service = Arc::new(
+        OrderService::new(db.clone(), mock_gateway_for_orders, inventory_service, event_dispatcher)
+            .with_order_numbers(OrderNumbers::new(config.order_numbers.clone())),
+    );
     
     // Initialize tax service
-    let tax_service = Arc::new(DefaultTaxService::new(db.pool().clone()));
+    let vies = ViesValidator::with_timeout(Duration::from_secs(config.tax.vies_timeout_secs));
+    let tax_service = Arc::new(
+        DefaultTaxService::with_validator(db.pool().clone(), vies).with_vat_config(&config.tax),
+    );
     
     // Initialize shipping provider factory
     let shipping_factory = Arc::new(ShippingProviderFactory::from_config(&config.shipping));
@@ -484,18 +610,58 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
     
     // Initialize checkout service
     let checkout_config = CheckoutConfig::default();
-    let checkout_service = Arc::new(CheckoutService::new(
+    let delivery_scheduler = DeliveryScheduler::from_config(&config.delivery)?;
+    let checkout_fields = CheckoutFieldSchema::from_config(&config.checkout_fields)?;
+    let mut returns_config = config.returns.clone();
+    returns_config.address = returns_config.address.or_else(|| config.shipping.origin.clone());
+    let mut checkout_service = CheckoutService::new(
         cart_service.clone(),
         tax_service.clone(),
         order_service.clone(),
         Arc::new(MockPaymentGateway::new()),
         shipping_factory.clone(),
         checkout_config,
-    ));
+    )
+    .with_addons(Arc::new(PostgresAddonRepository::new(db.pool().clone())))
+    .with_delivery(delivery_scheduler.clone(), Arc::new(PostgresDeliveryRepository::new(db.pool().clone())))
+    .with_checkout_fields(checkout_fields.clone(), Arc::new(PostgresCheckoutFieldRepository::new(db.pool().clone())))
+    .with_gift_cards(Arc::new(PostgresGiftCardRepository::new(db.pool().clone())))
+    .with_purchase_limits(purchase_limits)
+    .with_pickup(Arc::new(PostgresPickupRepository::new(db.pool().clone())));
+    if let Some(redis) = &redis {
+        checkout_service = checkout_service.with_flash_sales(FlashSaleStore::new(redis.clone(), &config.flash_sales));
+    }
+    if config.landed_cost.enabled {
+        let origin_country = config.landed_cost.origin_country.clone()
+            .or_else(|| config.shipping.origin.as_ref().map(|origin| origin.country.clone()))
+            .ok_or_else(|| rcommerce_core::Error::Config(
+                "landed_cost.origin_country or [shipping] origin is required for landed cost quotes".to_string()
+            ))?;
+        info!("Quoting duties and import taxes for orders shipping outside {}", origin_country);
+        checkout_service = checkout_service.with_landed_cost(
+            Arc::new(HsCodeEstimator::new(config.landed_cost.clone(), origin_country)),
+            Arc::new(PostgresCustomsRepository::new(db.pool().clone())),
+        );
+    }
+    if config.fraud.enabled {
+        info!("Screening checkout orders for fraud");
+        checkout_service = checkout_service.with_fraud(FraudScreen::new(
+            Arc::new(PostgresFraudRepository::new(db.pool().clone())),
+            config.fraud.clone(),
+        ));
+    }
+    let checkout_service = Arc::new(checkout_service);
     info!("Checkout service initialized");
 
+    // Load the master keys for stored secrets
+    let secrets = SecretBox::from_config(&config.secrets)?;
+    match secrets.current_key_id() {
+        Some(key_id) => info!("Stored secrets are sealed with master key {}", key_id),
+        None => warn!("No master key configured; webhook secrets are stored in plain text"),
+    }
+
     // Create app state
-    Ok(AppState::new(AppStateParams::new(
+    let app_state = AppState::new(AppStateParams::new(
         product_service,
         customer_service,
         auth_service,
@@ -511,7 +677,72 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
         tax_service,
         shipping_factory,
         checkout_service,
-    )))
+    )
+    .with_cache_warmup(config.cache.warmup.clone())
+    .with_response_cache(config.cache.responses.clone())
+    .with_capture(config.capture.clone())
+    .with_access_log(config.access_log.clone())
+    .with_audit(config.audit.clone())
+    .with_soft_delete(config.soft_delete.clone())
+    .with_websocket(config.websocket.clone())
+    .with_events(events, config.events.clone())
+    .with_formatting(config.formatting.clone())
+    .with_geoip(config.geoip.clone())
+    .with_order_archive(config.order_archive.clone())
+    .with_webhook_replay(config.webhook_replay.clone())
+    .with_subscription_billing(config.subscription_billing.clone())
+    .with_dunning(config.dunning.clone())
+    .with_stock_adjustments(config.stock_adjustments.clone())
+    .with_inventory(config.inventory.clone())
+    .with_fx(config.fx.clone())
+    .with_default_shipping_provider(config.shipping.default_provider.clone())
+    .with_delivery(delivery_scheduler)
+    .with_returns(returns_config)
+    .with_checkout_fields(checkout_fields)
+    .with_gift_cards(config.gift_cards.clone())
+    .with_flash_sales(config.flash_sales.clone())
+    .with_rate_limiting(config.rate_limiting.clone())
+    .with_idempotency(config.idempotency.clone())
+    .with_tls(config.tls.clone())
+    .with_secrets(secrets)
+    .with_email(config.notifications.email.clone())
+    .with_unsubscribe(config.notifications.unsubscribe.clone())
+    .with_sessions(config.sessions.clone())
+    .with_marketplaces(config.marketplaces.clone())
+    .with_automation(config.automation.clone())
+    .with_purchasing(config.purchasing.clone())
+    .with_supplier_feeds(config.supplier_feeds.clone())
+    .with_fulfillment(config.fulfillment.clone())
+    .with_reports(config.reports.clone())
+    .with_hosted_checkout(config.payment.hosted_checkout.clone())
+    .with_three_d_secure(config.payment.three_d_secure.clone())
+    .with_payment_capture(config.payment.capture.clone())
+    .with_payout_reconciliation(config.payment.reconciliation.clone())
+    .with_wallets(
+        config.payment.wallets.clone(),
+        ApplePayMerchantValidator::from_config(&config.payment.wallets.apple_pay)?,
+    )
+    .with_redirects(config.redirects.clone())
+    .with_media(config.media.clone(), media_storage)
+    .with_printing(config.printing.clone())
+    .with_observability(config.observability.clone(), config.features.metrics)
+    .with_analytics(config.analytics.clone())
+    .with_tracking(config.shipping.tracking.clone())
+    .with_scheduler(config.scheduler.clone())
+    .with_order_numbers(config.order_numbers.clone())
+    .with_fraud(config.fraud.clone()));
+
+    // Email branding and template overrides; the built-ins are sent if they can't be loaded
+    match crate::routes::notification_template::reload_theme(&app_state).await {
+        Ok(issues) => {
+            for issue in issues {
+                warn!("Email template {} ({}): {}", issue.template, issue.source.as_str(), issue.message);
+            }
+        }
+        Err(e) => warn!("Failed to load email template overrides, using the built-in templates: {}", e),
+    }
+
+    Ok(app_state)
 }
 
 /// Build CORS layer from configuration
@@ -551,17 +782,40 @@ fn build_cors_layer(config: &CorsConfig) -> CorsLayer {
 }
 
 /// Build the main API router
-fn build_router(app_state: AppState, tls_config: Option<TlsConfig>, cors_config: &CorsConfig) -> Router {
+fn build_router(
+    app_state: AppState,
+    tls_config: Option<TlsConfig>,
+    server_config: &ServerConfig,
+    admin_ui: &AdminUiConfig,
+) -> Router {
     // Configure CORS from config
-    let cors = build_cors_layer(cors_config);
+    let cors = build_cors_layer(&server_config.cors);
 
     // Build main router with API v1 routes
     let mut app = Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(crate::routes::cache::readiness))
+        .route("/metrics", get(crate::routes::metrics::metrics))
         .route("/", get(root))
+        .route(
+            "/.well-known/apple-developer-merchantid-domain-association",
+            get(crate::routes::wallet::domain_association),
+        )
+        .merge(crate::routes::uploads_router())
+        .merge(crate::routes::openapi_router())
+        .merge(crate::admin_ui::router(admin_ui))
         .nest("/api/v1", api_routes(app_state.clone()))
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
+        .layer(middleware::from_fn_with_state(app_state.clone(), capture_middleware))
+        .layer(middleware::from_fn_with_state(app_state.clone(), request_stats_middleware))
+        .layer(middleware::from_fn_with_state(app_state.clone(), access_log_middleware))
+        .layer(cors);
+
+    // Compress outside the capture and stats middleware, so they see plain bodies
+    if server_config.compression.enabled {
+        let predicate = DefaultPredicate::new().and(SizeAbove::new(server_config.compression.min_size_bytes));
+        app = app.layer(CompressionLayer::new().compress_when(predicate));
+    }
+    app = app.layer(TraceLayer::new_for_http());
 
     // Add security headers middleware (always, not just with TLS)
     // HSTS header will only be added when TLS is enabled
@@ -570,6 +824,9 @@ fn build_router(app_state: AppState, tls_config: Option<TlsConfig>, cors_config:
         security_headers_middleware,
     ));
 
+    // Outermost, so every middleware resolves client IPs the same way
+    app = app.layer(Extension(TrustedProxies::new(server_config.trusted_proxies.clone())));
+
     app.with_state(app_state)
 }
 
@@ -584,18 +841,79 @@ fn log_routes(config: &Config) {
     
     info!("Available routes ({}://localhost:{}):", protocol, port);
     info!("  GET  /health                      - Health check");
+    info!("  GET  /ready                       - Readiness (after cache warmup)");
+    if config.features.metrics {
+        info!("  GET  /metrics                     - Business metrics (Prometheus)");
+    }
     info!("  GET  /                            - API info");
+    info!("  GET  /api/openapi.json            - OpenAPI document of the storefront API");
+    info!("  GET  /api/docs                    - Swagger UI");
+    if config.admin_ui.enabled {
+        info!("  GET  {:<30} - Admin UI (staff sign-in)", config.admin_ui.path);
+    }
+    info!("  GET  /api/v1/admin/events/schema  - JSON Schemas of domain events and webhook payloads (webhooks:read)");
     info!("  GET  /api/v1/products             - List products");
+    info!("  GET  /api/v1/products/stream      - All matching products as one streamed JSON array");
     info!("  GET  /api/v1/products/:id         - Get product");
+    info!("  GET  /api/v1/products/:id/variants - List product variants");
+    info!("  GET  /api/v1/products/:id/options - List product options");
+    info!("  GET  /api/v1/storefront/products  - Read-only catalog (publishable key, allowed origins)");
+    if config.websocket.enabled {
+        info!("  GET  /api/v1/storefront/stock/events - Stock availability stream (server-sent events)");
+    }
     info!("  GET  /api/v1/customers            - List customers");
     info!("  GET  /api/v1/customers/:id        - Get customer");
+    info!("  GET  /api/v1/customers/:id/addresses - Customer address book");
+    info!("  POST /api/v1/customers/:id/addresses - Add an address (carrier-validated)");
+    info!("  GET  /api/v1/geo                  - Detected location (currency, locale, country)");
+    info!("  PUT  /api/v1/geo/override         - Override storefront currency/locale/country");
+    info!("  GET  /api/v1/customers/me/invoices - List own invoices");
+    info!("  GET  /api/v1/customers/me/invoices/:order_id/pdf - Download invoice PDF");
+    info!("  GET  /api/v1/customers/me/receipts - List own payment receipts");
+    info!("  GET  /api/v1/customers/me/orders - Order history with shipment tracking");
+    info!("  POST /api/v1/customers/me/orders/:id/reorder - Add an order's lines to the cart again");
+    info!("  GET  /api/v1/customers/me/payment-methods - Saved payment methods (POST to tokenize and save one)");
+    info!("  PUT  /api/v1/customers/me/notification-preferences - Update notification preferences");
+    info!("  POST /api/v1/unsubscribe/:token     - One-click unsubscribe from marketing emails (signed link)");
+    info!("  PUT  /api/v1/unsubscribe/:token/preferences - Change notification preferences (signed link)");
+    info!("  POST /api/v1/customers/me/close - Close the account");
+    info!("  GET  /api/v1/customers/me/notifications - In-app notifications with the unread count");
+    info!("  POST /api/v1/customers/me/notifications/read-all - Mark all in-app notifications read");
+    if config.websocket.enabled {
+        info!("  GET  /api/v1/customers/me/notifications/live - New notifications and unread counts (WebSocket)");
+    }
+    info!("  GET  /api/v1/formatting           - Price display rules");
+    info!("  GET  /api/v1/formatting/rule      - Display rule for locale/currency");
     info!("  GET  /api/v1/orders               - List orders");
     info!("  GET  /api/v1/orders/:id           - Get order");
     info!("  POST /api/v1/checkout/initiate    - Initiate checkout (with tax/shipping calc)");
     info!("  POST /api/v1/checkout/shipping    - Select shipping method");
     info!("  POST /api/v1/checkout/complete    - Complete checkout");
+    info!("       (POST routes accept an Idempotency-Key header; retries replay the first response)");
     info!("  POST /api/v1/auth/login           - Login");
     info!("  POST /api/v1/auth/register        - Register");
+    info!("  POST /api/v1/auth/refresh         - Rotate the refresh token for a new access token");
+    info!("  POST /api/v1/auth/logout          - Revoke the current login session");
+    info!("  POST /api/v1/auth/logout-all      - Revoke every login and cookie session");
+    info!("  GET  /api/v1/auth/sessions        - List signed-in devices");
+    info!("  DELETE /api/v1/auth/sessions/:id  - Sign out one device");
+    info!("  POST /api/v1/auth/session         - Cookie session login (when [sessions] enabled)");
+    info!("  GET  /api/v1/auth/session         - Current session and CSRF token");
+    info!("  DELETE /api/v1/auth/session       - Cookie session logout (CSRF header required)");
+    info!("  POST /api/v1/auth/2fa/verify      - Finish a login with a two-factor code");
+    info!("  GET  /api/v1/auth/2fa             - Two-factor status");
+    info!("  POST /api/v1/auth/2fa/enroll      - Start TOTP enrollment (secret and QR URI)");
+    info!("  POST /api/v1/auth/2fa/confirm     - Enable 2FA with a first code; returns backup codes");
+    info!("  POST /api/v1/auth/2fa/disable     - Disable 2FA (code or backup code)");
+    info!("  POST /api/v1/auth/2fa/backup-codes - Replace backup codes");
+    info!("  DELETE /api/v1/admin/customers/:id/2fa - Reset an account's 2FA (users:admin)");
+    info!("  GET  /api/v1/auth/oauth/providers - Enabled social login providers");
+    info!("  GET  /api/v1/auth/oauth/:provider/authorize - Log in with Google, Apple or GitHub");
+    info!("  GET  /api/v1/auth/oauth/:provider/callback  - Provider redirect back (POST for Apple)");
+    info!("  POST /api/v1/auth/oauth/login     - Exchange a social login code for tokens");
+    info!("  POST /api/v1/auth/oauth/session   - Exchange a social login code for a cookie session");
+    info!("  GET  /api/v1/auth/oauth/identities - Linked provider accounts");
+    info!("  DELETE /api/v1/auth/oauth/identities/:id - Unlink a provider account");
     info!("  POST /api/v1/carts/guest          - Create guest cart");
     info!("  GET  /api/v1/carts/me             - Get customer cart");
     info!("  POST /api/v1/carts/merge          - Merge carts");
@@ -605,7 +923,25 @@ fn log_routes(config: &Config) {
     info!("  POST /api/v1/payments             - Create payment");
     info!("  GET  /api/v1/payments/:id         - Get payment status");
     info!("  POST /api/v1/payments/:id/complete - Complete payment");
+    info!("  GET  /api/v1/payments/sessions/:id - Poll a payment session (3-D Secure challenges)");
+    info!("  GET  /api/v1/payments/sessions/:id/return - Return from a 3-D Secure challenge (public)");
     info!("  POST /api/v1/payments/:id/refund  - Refund payment");
+    info!("  GET  /api/v1/admin/orders         - Browse orders (?status=&q=&limit=&offset=, orders:read)");
+    info!("  GET  /api/v1/admin/orders/:id     - Order with items and payments (orders:read)");
+    info!("  POST /api/v1/admin/payments/:id/refund - Refund a recorded payment (payments:write)");
+    info!("  GET  /api/v1/admin/payments/:id/captures - Authorization and its captures (payments:read)");
+    info!("  POST /api/v1/admin/payments/:id/capture - Capture an authorized payment (payments:write)");
+    info!("  POST /api/v1/admin/payments/:id/void - Void what is left of an authorization (payments:write)");
+    info!("  PUT  /api/v1/admin/orders/:id/capture-on-fulfillment - Capture as the order ships (orders:write)");
+    info!("  GET  /api/v1/admin/payments/reconciliation - Payouts matched against payments (payments:read)");
+    info!("  GET  /api/v1/admin/payments/payouts - Imported gateway payouts (payments:read)");
+    info!("  GET  /api/v1/admin/payments/payouts/:id - Payout with its matched transactions (payments:read)");
+    info!("  POST /api/v1/admin/payments/payouts/import - Import and match payouts now (payments:write)");
+    info!("  GET  /api/v1/admin/admin/api-keys - List API keys (admin)");
+    info!("  POST /api/v1/admin/admin/api-keys - Create an API key; the key is shown once (admin)");
+    info!("  POST /api/v1/admin/admin/api-keys/:prefix/revoke - Revoke an API key (admin)");
+    info!("  POST /api/v1/email/events/sendgrid - SendGrid bounce/complaint events");
+    info!("  POST /api/v1/email/events/ses - SES bounce/complaint events via SNS (?token=)");
     info!("  GET  /api/v1/admin/statistics/dashboard - Dashboard stats (admin)");
     info!("  GET  /api/v1/admin/statistics/sales     - Sales statistics (admin)");
     info!("  GET  /api/v1/admin/statistics/orders    - Order statistics (admin)");
@@ -613,6 +949,154 @@ fn log_routes(config: &Config) {
     info!("  GET  /api/v1/admin/statistics/customers - Customer statistics (admin)");
     info!("  GET  /api/v1/admin/statistics/revenue   - Revenue trends (admin)");
     info!("  GET  /api/v1/admin/statistics/compare   - Period comparison (admin)");
+    info!("  GET  /api/v1/admin/cache/warmup         - Last cache warmup report (admin)");
+    info!("  POST /api/v1/admin/cache/warm           - Re-run cache warmup (admin)");
+    info!("  POST /api/v1/admin/cache/purge          - Purge cached responses by surrogate key or URL pattern (settings:write)");
+    info!("  GET  /api/v1/admin/exchange-rates       - Exchange rate history (admin)");
+    info!("  POST /api/v1/admin/exchange-rates       - Record exchange rate (admin)");
+    info!("  GET  /api/v1/products/:id/price         - Product price in a currency (price lists, FX, customer groups)");
+    info!("  GET  /api/v1/admin/price-lists          - Price lists (admin)");
+    info!("  GET  /api/v1/addons                     - Checkout add-ons (gift wrap, messages, assembly)");
+    info!("  POST /api/v1/carts/:cart_id/addons      - Choose an add-on for a cart or cart item");
+    info!("  GET  /api/v1/admin/orders/:id/pick-list - Pick list with add-on instructions (admin)");
+    info!("  GET  /api/v1/checkout/delivery-options  - Bookable delivery dates and windows");
+    info!("  GET  /api/v1/admin/orders/:id/packing-slip - Packing slip with delivery instructions (admin)");
+    info!("  GET  /api/v1/checkout/fields            - Custom checkout field schema");
+    info!("  PUT  /api/v1/admin/orders/:id/custom-fields - Correct an order's custom field answers (admin)");
+    info!("  POST /api/v1/gift-cards/balance         - Check a gift card balance");
+    info!("  POST /api/v1/admin/gift-cards           - Issue a gift card (admin)");
+    info!("  POST /api/v1/flash-sales/:id/enter      - Enter a flash sale waiting room");
+    info!("  POST /api/v1/admin/flash-sales/:id/start - Put a flash sale live (admin)");
+    info!("  POST /api/v1/admin/purchase-limits      - Per-customer/household purchase limit (admin)");
+    info!("  POST /api/v1/admin/allocation-lists/:id/members - Give customers early access (admin)");
+    info!("  POST /api/v1/orders/:id/returns         - Request a return");
+    info!("  POST /api/v1/admin/returns/:id/approve  - Approve a return and create its label (admin)");
+    info!("  POST /api/v1/admin/returns/:id/receive  - Receive a return and refund it (admin)");
+    info!("  GET  /api/v1/admin/roles                - Staff roles and permissions (users:read)");
+    info!("  POST /api/v1/admin/customers/:id/roles  - Assign a staff role (users:admin)");
+    info!("  GET  /api/v1/admin/permissions/preview - What a role, API key or account may do (users:read)");
+    info!("  POST /api/v1/admin/marketplaces/listings - List a product on Amazon or eBay (products:write)");
+    info!("  GET  /api/v1/admin/marketplaces/orders  - Imported marketplace orders (products:read)");
+    info!("  POST /api/v1/admin/automation/rules     - Create an automation rule (settings:write)");
+    info!("  POST /api/v1/admin/automation/rules/test - Dry-run a rule against an order or customer (settings:write)");
+    info!("  GET  /api/v1/admin/automation/runs      - Automation execution log (settings:read)");
+    info!("  POST /api/v1/admin/suppliers            - Create a supplier (inventory:write)");
+    info!("  POST /api/v1/admin/purchase-orders      - Draft a purchase order (inventory:write)");
+    info!("  POST /api/v1/admin/purchase-orders/:id/receive - Receive a delivery into stock (inventory:write)");
+    info!("  PUT  /api/v1/admin/suppliers/:id/dispatch - Send purchase orders by email, SFTP or manually (inventory:write)");
+    info!("  POST /api/v1/purchase-orders/acknowledge/:token - Supplier acknowledgment of an emailed purchase order");
+    info!("  GET  /api/v1/admin/purchase-order-discrepancies - Acknowledgment and receipt discrepancies (inventory:read)");
+    info!("  PUT  /api/v1/admin/suppliers/:id/feed   - Configure a supplier stock and price feed (inventory:write)");
+    info!("  POST /api/v1/supplier-feeds/:supplier_id - Signed feed pushed by a supplier");
+    info!("  GET  /api/v1/admin/dropship-orders      - Orders routed to dropship suppliers (inventory:read)");
+    info!("  POST /api/v1/admin/orders/:id/shipments - Ship an order by its shipping preference, backorder the rest (orders:write)");
+    info!("  PUT  /api/v1/orders/:id/shipping-preference - Ship an order complete or partial");
+    info!("  POST /api/v1/admin/shipments/:id/label - Buy a label on the location's carrier account (orders:write)");
+    info!("  GET  /api/v1/admin/shipments/:id/label - Download the stored label as PDF, ZPL or PNG (orders:read)");
+    info!("  POST /api/v1/admin/shipments/labels/batch - Print the labels of a pick list of orders (orders:write)");
+    info!("  POST /api/v1/shipping/tracking/:provider - Tracking webhook of EasyPost or ShipStation (token in the URL)");
+    info!("  POST /api/v1/admin/shipping/tracking/webhooks - Register tracking webhooks with the aggregators (orders:write)");
+    info!("  POST /api/v1/admin/shipments/:id/tracking/refresh - Fetch a shipment's tracking now (orders:write)");
+    info!("  GET  /api/v1/pickup/locations       - Pickup locations with hours and instructions");
+    info!("  GET  /api/v1/products/:id/pickup     - Stock of a product at each pickup location");
+    info!("  PUT  /api/v1/admin/pickup/locations/:id - Offer pickup at an inventory location (inventory:write)");
+    info!("  POST /api/v1/admin/shipments/:id/ready-for-pickup - Pickup order packed, customer emailed (orders:write)");
+    info!("  POST /api/v1/admin/shipments/:id/picked-up - Pickup order collected, customer emailed (orders:write)");
+    info!("  POST /api/v1/admin/backorders/release - Ship backorders back in stock (orders:write)");
+    info!("  POST /api/v1/admin/reports/subscriptions - Email a report on a schedule (reports:write)");
+    info!("  GET  /api/v1/admin/reports/deliveries - Report delivery history (reports:read)");
+    info!("  POST /api/v1/admin/reports/deliveries/:id/resend - Email a report again (reports:write)");
+    info!("  GET  /api/v1/reports/download/:token - Report file linked from report emails");
+    info!("  POST /api/v1/storefront/events - Track storefront funnel events (publishable key)");
+    info!("  GET  /api/v1/admin/analytics/funnel - Sessions per checkout step (reports:read)");
+    info!("  GET  /api/v1/admin/sales/revenue     - Revenue per day, week or month (reports:read)");
+    info!("  GET  /api/v1/admin/sales/top-products - Best selling products (reports:read)");
+    info!("  GET  /api/v1/admin/sales/channels    - Orders and conversion per channel (reports:read)");
+    info!("  GET  /api/v1/admin/sales/refunds     - Refund rates (reports:read)");
+    info!("  GET  /api/v1/admin/sales/average-order-value - Average order value (reports:read)");
+    info!("  POST /api/v1/orders/:id/hosted-checkout - Hosted checkout page for an order");
+    info!("  POST /api/v1/admin/orders/:id/hosted-checkouts - Checkout session or payment link (orders:write)");
+    info!("  POST /api/v1/admin/hosted-checkouts/:id/expire - Close an open checkout (orders:write)");
+    info!("  GET  /api/v1/payments/wallets - Apple Pay and Google Pay settings");
+    info!("  POST /api/v1/payments/apple-pay/session - Apple Pay merchant session");
+    info!("  POST /api/v1/admin/payments/apple-pay/domains - Register an Apple Pay domain with a gateway (payments:write)");
+    info!("  GET  /.well-known/apple-developer-merchantid-domain-association - Apple Pay domain verification");
+    info!("  GET  /api/v1/admin/catalog/snapshot - Catalog for promotion between instances (products:read)");
+    info!("  POST /api/v1/admin/catalog/changes - Apply promoted catalog changes (products:write)");
+    info!("  GET  /api/v1/redirects/resolve - Where a storefront path moved (old slugs, manual redirects)");
+    info!("  GET  /api/v1/products/by-slug/:slug - Get product by slug (301 for an old slug)");
+    info!("  POST /api/v1/admin/redirects - Create a manual redirect (products:write)");
+    info!("  POST /api/v1/admin/redirects/import - Import redirects from CSV (products:write)");
+    info!("  POST /api/v1/admin/customer-groups - Create a customer group (customers:write)");
+    info!("  PUT  /api/v1/admin/customer-groups/:id/prices - Set a group's product price tier (customers:write)");
+    info!("  GET  /api/v1/products?category=&collection= - Products in a category tree or collection");
+    info!("  GET  /api/v1/products?attr=color:red;width:10..20 - Products by attribute values, with facets");
+    info!("  POST /api/v1/admin/attributes      - Create a product attribute (products:write)");
+    info!("  PUT  /api/v1/admin/products/:id/attributes - Set a product's attribute values (products:write)");
+    info!("  GET  /api/v1/categories            - Category tree");
+    info!("  GET  /api/v1/collections           - Published collections");
+    info!("  POST /api/v1/admin/categories      - Create a category (products:write)");
+    info!("  POST /api/v1/admin/collections     - Create a manual or smart collection (products:write)");
+    info!("  POST /api/v1/admin/collections/:id/products - Add products to a manual collection (products:write)");
+    info!("  POST /api/v1/admin/products/:id/images - Upload a product image (products:write)");
+    info!("  POST /api/v1/admin/products/:id/images/from-url - Download a product image (products:write)");
+    info!("  PUT  /api/v1/admin/products/:id/images/order - Put product images in order (products:write)");
+    info!("  PUT  /api/v1/admin/products/:id/customs - Set a product's HS code and origin (products:write)");
+    info!("  GET  /uploads/*key                 - Uploaded product images (local storage)");
+    info!("  POST /api/v1/admin/print-batches   - Merge shipping labels or packing slips into one PDF (orders:write)");
+    info!("  GET  /api/v1/admin/print-batches/:id/pdf - Print batch PDF (orders:read)");
+    info!("  POST /api/v1/admin/print-batches/:id/printed - Record a batch printed (orders:write)");
+    info!("  GET  /api/v1/admin/incidents       - Anomaly incidents (settings:read)");
+    info!("  POST /api/v1/admin/incidents/:id/acknowledge - Acknowledge an incident (settings:write)");
+    info!("  POST /api/v1/admin/incidents/:id/resolve - Resolve an incident (settings:write)");
+    info!("  GET  /api/v1/admin/fraud/reviews   - Orders held for fraud review (orders:read)");
+    info!("  POST /api/v1/admin/fraud/reviews/:order_id/approve - Release a held order (orders:write)");
+    info!("  POST /api/v1/admin/fraud/reviews/:order_id/reject - Cancel a held order (orders:write)");
+    info!("  GET  /api/v1/admin/orders/:id/risk - Risk score and signals of an order (orders:read)");
+    info!("  GET  /api/v1/admin/notification-templates/variables - Template variables catalog (admin)");
+    info!("  PUT  /api/v1/admin/notification-templates/:name - Save email template (admin)");
+    info!("  GET  /api/v1/admin/products/:id/price-history - Price change history (admin)");
+    info!("  POST /api/v1/products/:id/variants - Add product variant (admin)");
+    info!("  PUT  /api/v1/products/:id/variants/:variant_id - Update product variant (admin)");
+    info!("  GET  /api/v1/admin/orders/archive - Order archive status (admin)");
+    info!("  POST /api/v1/admin/orders/archive/run - Archive finished orders now (admin)");
+    info!("  GET  /api/v1/admin/access-denials - Requests refused by mTLS or API key IP allowlists (admin)");
+    info!("  GET  /api/v1/export/:entity         - Export products, customers or orders as CSV/JSON (exports:read)");
+    info!("  GET  /api/v1/admin/partitions - Table partitions (admin)");
+    info!("  POST /api/v1/admin/partitions/ensure - Create upcoming partitions (admin)");
+    info!("  POST /api/v1/admin/webhooks/:id/replays - Replay webhook events (admin)");
+    info!("  GET  /api/v1/admin/webhooks/:id/replays/:replay_id - Webhook replay progress (admin)");
+    info!("  GET  /api/v1/subscription-plans - Subscription plans");
+    info!("  POST /api/v1/subscription-plans/:id/subscribe - Subscribe to a plan");
+    info!("  POST /api/v1/admin/subscription-plans - Create subscription plan (admin)");
+    info!("  PUT  /api/v1/admin/subscription-plans/:id - Update subscription plan (admin)");
+    info!("  POST /api/v1/inventory/adjustments - Request stock adjustment (staff)");
+    info!("  POST /api/v1/inventory/adjustments/:id/approve - Approve stock adjustment (approvers)");
+    info!("  GET  /api/v1/admin/inventory/locations - Active inventory locations (inventory:read)");
+    info!("  GET  /api/v1/admin/inventory/levels - Stock by location (?product_id= or ?sku=, inventory:read)");
+    info!("  POST /api/v1/pos/registers/:id/sessions - Open register session (staff)");
+    info!("  POST /api/v1/pos/sessions/:id/close - Close register session with cash count (staff)");
+    info!("  GET  /api/v1/pos/sessions/:id/z-report - Z-report (?format=csv)");
+    if config.access_log.enabled {
+        info!("  GET  /api/v1/admin/access-log           - Search the access log of API requests (admin)");
+    }
+    if config.audit.enabled {
+        info!("  GET  /api/v1/admin/audit-log            - Search the audit log of API mutations (admin)");
+        info!("  GET  /api/v1/admin/audit-log/:id        - Audit entry with its changes (admin)");
+    }
+    info!("  DELETE /api/v1/admin/{{products,customers,orders}}/:id - Soft-delete (restorable for {} days)", config.soft_delete.retention_days);
+    info!("  POST /api/v1/admin/{{products,customers,orders}}/:id/restore - Restore a deleted record");
+    info!("  GET  /api/v1/admin/{{products,customers,orders}}/deleted - Deleted records");
+    if config.websocket.enabled {
+        info!("  GET  /api/v1/admin/live                 - Live order, payment, low stock and shipment events (WebSocket)");
+    }
+    info!("  POST /api/v1/admin/in-app-notifications - Add a notification to someone's bell (admin)");
+    info!("  GET  /api/v1/admin/jobs/failed          - Dead-lettered recurring jobs (settings:read)");
+    info!("  POST /api/v1/admin/jobs/failed/:id/retry - Run a dead-lettered job again (settings:write)");
+    info!("  POST /api/v1/admin/jobs/failed/:id/discard - Discard a dead-lettered job (settings:write)");
+    if config.capture.enabled {
+        info!("  GET  /api/v1/admin/capture              - Download captured traffic (admin)");
+    }
 
     if config.tls.enabled {
         info!("  (HTTP port {} redirects to HTTPS)", config.tls.http_port);
@@ -661,18 +1145,47 @@ fn api_routes(app_state: AppState) -> Router<AppState> {
     // Public routes (no auth required)
     let public_routes = Router::new()
         .merge(crate::routes::auth_public_router())
+        .merge(crate::routes::oauth_public_router())
         // Public cart routes (guest cart creation, get cart by ID)
         .merge(crate::routes::cart_public_router())
+        // Price display rules for storefronts
+        .merge(crate::routes::formatting_router())
+        // Detected location and storefront override
+        .merge(crate::routes::geo_router())
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), geoip_middleware))
         // Webhooks are public (signature verification handles security)
         .route(
             "/webhooks/:gateway_id",
             post(crate::routes::payment::handle_webhook),
-        );
+        )
+        // Customers returning from 3-D Secure challenges
+        .merge(crate::routes::payment_public_router())
+        // Email provider bounce/complaint events (signature or URL token)
+        .merge(crate::routes::email_events_router())
+        // Unsubscribe links from marketing emails (signed token in the URL)
+        .merge(crate::routes::unsubscribe_router())
+        // Supplier stock and price feeds (signed with the feed's webhook secret)
+        .merge(crate::routes::supplier_feeds_public_router())
+        .merge(crate::routes::po_dispatch_public_router())
+        // Report file links from report emails (token in the URL)
+        .merge(crate::routes::report_download_router())
+        // Carrier tracking updates (token in the URL)
+        .merge(crate::routes::tracking_router())
+        // Admin live events socket (staff token checked by the handler)
+        .merge(crate::routes::live_router())
+        // Notification bell socket (token checked by the handler)
+        .merge(crate::routes::in_app_notification_live_router());
 
     // Protected routes (API key auth required)
     let protected_routes = Router::new()
         .merge(crate::routes::product_router())
+        .merge(crate::routes::category_router())
+        .merge(crate::routes::collection_router())
+        .merge(crate::routes::variant_router())
         .merge(crate::routes::customer_router())
+        .merge(crate::routes::invoice_router())
+        .merge(crate::routes::account_router())
+        .merge(crate::routes::in_app_notification_router())
         .merge(crate::routes::order_router())
         .merge(crate::routes::checkout_router())
         // Protected cart routes (customer cart, merge, modify items)
@@ -680,22 +1193,118 @@ fn api_routes(app_state: AppState) -> Router<AppState> {
         .merge(crate::routes::coupon_router())
         // Auth routes requiring API key (password reset request)
         .merge(crate::routes::auth_protected_router())
+        .merge(crate::routes::two_factor_router())
+        .merge(crate::routes::oauth_router())
         // Payment routes except webhooks
         .merge(crate::routes::payment::payment_routes())
+        .merge(crate::routes::hosted_checkout_router())
+        .merge(crate::routes::wallet_router())
+        .merge(crate::routes::redirects_router())
+        .merge(crate::routes::subscription_plan_router())
+        .merge(crate::routes::stock_adjustment_router())
+        .merge(crate::routes::register_router())
+        .merge(crate::routes::price_list_router())
+        .merge(crate::routes::address_router())
+        .merge(crate::routes::addon_router())
+        .merge(crate::routes::delivery_router())
+        .merge(crate::routes::pickup_router())
+        .merge(crate::routes::checkout_field_router())
+        .merge(crate::routes::gift_card_router())
+        .merge(crate::routes::flash_sale_router())
+        .merge(crate::routes::returns_router())
+        .merge(crate::routes::shipments_router())
+        .merge(crate::routes::analytics_router())
+        // Runs after auth: records the caller of each mutation
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), audit_middleware))
+        // Runs after auth: cached responses are only served to authenticated callers
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), response_cache_middleware))
+        // Runs outside the response cache: cached catalog responses get ETags too
+        .route_layer(middleware::from_fn(conditional_get_middleware))
+        // Runs after auth: keys are scoped to the customer
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency_middleware))
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             auth_middleware,
-        ));
-
-    // Admin routes (admin auth required)
+        ))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), geoip_middleware));
+
+    // Storefront routes (publishable key from an allowed origin required)
+    let storefront_routes = Router::new()
+        .merge(crate::routes::storefront_router())
+        .merge(crate::routes::stock_events_router())
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), response_cache_middleware))
+        .route_layer(middleware::from_fn(conditional_get_middleware))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), storefront_key_middleware))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), geoip_middleware));
+
+    // Admin routes (staff permission for each route required)
     let admin_routes = Router::new()
         .nest("/admin", crate::routes::admin_router())
+        .merge(crate::routes::order_admin_router())
+        .merge(crate::routes::payment_admin_router())
+        .merge(crate::routes::stock_adjustment_admin_router())
         .merge(crate::routes::statistics_router())
+        .merge(crate::routes::cache_router())
+        .merge(crate::routes::capture_router())
+        .merge(crate::routes::exchange_rate_router())
+        .merge(crate::routes::notification_template_router())
+        .merge(crate::routes::in_app_notification_admin_router())
+        .merge(crate::routes::price_history_router())
+        .merge(crate::routes::order_archive_router())
+        .merge(crate::routes::access_denial_router())
+        .merge(crate::routes::access_log_router())
+        .merge(crate::routes::two_factor_admin_router())
+        .merge(crate::routes::partitions_router())
+        .merge(crate::routes::webhook_replay_router())
+        .merge(crate::routes::event_schema_admin_router())
+        .merge(crate::routes::variant_admin_router())
+        .merge(crate::routes::subscription_plan_admin_router())
+        .merge(crate::routes::price_list_admin_router())
+        .merge(crate::routes::addon_admin_router())
+        .merge(crate::routes::delivery_admin_router())
+        .merge(crate::routes::checkout_field_admin_router())
+        .merge(crate::routes::gift_card_admin_router())
+        .merge(crate::routes::flash_sale_admin_router())
+        .merge(crate::routes::purchase_limit_admin_router())
+        .merge(crate::routes::export_router())
+        .merge(crate::routes::returns_admin_router())
+        .merge(crate::routes::roles_admin_router())
+        .merge(crate::routes::marketplace_admin_router())
+        .merge(crate::routes::automation_admin_router())
+        .merge(crate::routes::purchasing_admin_router())
+        .merge(crate::routes::po_dispatch_admin_router())
+        .merge(crate::routes::supplier_feeds_admin_router())
+        .merge(crate::routes::shipments_admin_router())
+        .merge(crate::routes::tracking_admin_router())
+        .merge(crate::routes::pickup_admin_router())
+        .merge(crate::routes::reports_admin_router())
+        .merge(crate::routes::analytics_admin_router())
+        .merge(crate::routes::sales_admin_router())
+        .merge(crate::routes::hosted_checkout_admin_router())
+        .merge(crate::routes::wallet_admin_router())
+        .merge(crate::routes::catalog_promotion_admin_router())
+        .merge(crate::routes::redirects_admin_router())
+        .merge(crate::routes::customer_group_admin_router())
+        .merge(crate::routes::attribute_admin_router())
+        .merge(crate::routes::category_admin_router())
+        .merge(crate::routes::collection_admin_router())
+        .merge(crate::routes::media_admin_router())
+        .merge(crate::routes::customs_admin_router())
+        .merge(crate::routes::printing_admin_router())
+        .merge(crate::routes::incident_admin_router())
+        .merge(crate::routes::fraud_admin_router())
+        .merge(crate::routes::reconciliation_admin_router())
+        .merge(crate::routes::jobs_admin_router())
+        .merge(crate::routes::audit_router())
+        .merge(crate::routes::soft_delete_router())
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), audit_middleware))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency_middleware))
         .route_layer(middleware::from_fn_with_state(app_state, admin_middleware));
 
     Router::new()
         .merge(public_routes)
         .merge(protected_routes)
+        .merge(storefront_routes)
         .merge(admin_routes)
 }
 
@@ -273,7 +273,7 @@ fn build_tls_router(app_state: AppState, tls_config: TlsConfig) -> Router {
 async fn create_app_state(config: &Config) -> Result<AppState> {
     use rcommerce_core::cache::RedisPool;
     use rcommerce_core::payment::agnostic::PaymentService;
-    use rcommerce_core::repository::{create_pool, CustomerRepository, Database, ProductRepository, PostgresApiKeyRepository, PostgresSubscriptionRepository};
+    use rcommerce_core::repository::{create_pool, create_replica_set, CustomerRepository, Database, ProductRepository, PostgresApiKeyRepository, PostgresSubscriptionRepository};
     use rcommerce_core::services::{AuthService, CustomerService, ProductService, CouponService, SubscriptionService};
 
     // Initialize database
@@ -287,7 +287,7 @@ async fn create_app_state(config: &Config) -> Result<AppState> {
     )
     .await?;
 
-    let db = Database::new(pool.clone());
+    let db = Database::new(pool.clone()).with_read_replicas(create_replica_set(&config.database)?);
 
     // Initialize repositories
     let product_repo = ProductRepository::new(db.clone());
@@ -1,14 +1,29 @@
 use std::sync::Arc;
 
-use rcommerce_core::cache::RedisPool;
-use rcommerce_core::payment::agnostic::PaymentService;
-use rcommerce_core::repository::{Database, PostgresApiKeyRepository, PostgresSubscriptionRepository};
-use rcommerce_core::services::{AuthService, CustomerService, ProductService, SubscriptionService, CouponService, CartService, CheckoutService, OrderService};
-use rcommerce_core::shipping::ShippingProviderFactory;
+use rcommerce_core::cache::{AuthSessionStore, CacheWarmer, FlashSaleStore, RedisPool, ResponseCache, WarmupState};
+use rcommerce_core::config::{AccessLogConfig, AuditConfig, AnalyticsConfig, JobSchedulerConfig, TrackingConfig, AutomationConfig, FulfillmentConfig, MediaConfig, PrintingConfig, HostedCheckoutConfig, ThreeDSecureConfig, PaymentCaptureConfig, PayoutReconciliationConfig, ObservabilityConfig, RedirectsConfig, WalletConfig, PurchasingConfig, SupplierFeedsConfig, ReportsConfig, SessionConfig, SoftDeleteConfig, MarketplaceConfig, CacheWarmupConfig, ResponseCacheConfig, CaptureConfig, DunningConfig, EmailConfig, FormattingConfig, FraudConfig, FlashSalesConfig, FxConfig, GeoIpConfig, GiftCardsConfig, IdempotencyConfig, OrderArchiveConfig, RateLimitConfig, ReturnsConfig, StockAdjustmentConfig, SubscriptionBillingConfig, TlsConfig, UnsubscribeConfig, WebhookReplayConfig};
+use rcommerce_core::automation::AutomationEngine;
+use rcommerce_core::reports::{ReportService, SalesReportService};
+use rcommerce_core::analytics::AnalyticsService;
+use rcommerce_core::jobs::{locks_from_config, DistributedLock};
+use rcommerce_core::inventory::{InventoryConfig, InventoryService, PurchaseOrderDispatchService, PurchasingService, StockAdjustmentService, SupplierFeedService};
+use rcommerce_core::observability::MetricsService;
+use rcommerce_core::order::{OrderNumberConfig, OrderNumbers, ShipmentService};
+use rcommerce_core::payment::agnostic::{CaptureMethod, PaymentService};
+use rcommerce_core::payment::wallet::ApplePayMerchantValidator;
+use rcommerce_core::repository::{Database, PostgresAccessDenialRepository, PostgresAccessLogRepository, PostgresAnalyticsRepository, PostgresAuditRepository, PostgresAutomationRepository, PostgresCatalogRepository, PostgresCustomerGroupRepository, PostgresAttributeRepository, PostgresCategoryRepository, PostgresCollectionRepository, PostgresCustomsRepository, PostgresMetricsRepository, PostgresPrintBatchRepository, PostgresProductImageRepository, PostgresRedirectRepository, PostgresSecretRepository, PostgresShipmentRepository, PostgresSoftDeleteRepository, PostgresTrackingRepository, PostgresPickupRepository, PostgresAddonRepository, PostgresAddressRepository, PostgresApiKeyRepository, PostgresCheckoutFieldRepository, PostgresDeliveryRepository, PostgresExportRepository, PostgresFlashSaleRepository, PostgresFraudRepository, PostgresGiftCardRepository, PostgresHostedCheckoutRepository, PostgresIdempotencyRepository, PostgresLoginSessionRepository, PostgresMarketplaceRepository, PostgresNotificationRepository, PostgresOAuthRepository, PostgresOrderArchiveRepository, PostgresOutboxRepository, PostgresPaymentCaptureRepository, PostgresPaymentSessionRepository, PostgresPayoutRepository, PostgresAccountRepository, PostgresPriceListRepository, PostgresPurchaseLimitRepository, PostgresPurchaseOrderRepository, PostgresPoDispatchRepository, PostgresSupplierFeedRepository, PostgresTwoFactorRepository, PostgresRegisterRepository, PostgresReportRepository, PostgresReturnRepository, PostgresSalesReportRepository, PostgresRoleRepository, PostgresStockAdjustmentRepository, PostgresSubscriptionPlanRepository, PostgresSubscriptionRepository, PostgresWebhookDispatchRepository, PostgresWebhookReplayRepository};
+use rcommerce_core::services::{AccessLogService, JwtClaims, AuditService, AddonService, CatalogPromotionService, CustomerGroupService, AttributeService, CategoryService, CollectionService, MediaService, PrintService, RedirectService, AddressBookService, AuthService, CheckoutFieldSchema, CheckoutFieldService, DeliveryService, ExportService, FlashSaleService, FraudScreen, GiftCardService, HostedCheckoutService, IdempotencyService, LoginSessionService, CustomerService, OAuthService, GatewayRefunder, CaptureOnShipment, PaymentCaptureService, PaymentSessionService, PayoutReconciliationService, AccountService, ProductService, ReturnService, RoleService, SubscriptionService, CouponService, CartService, CheckoutService, OrderService, FormattingService, GeoIpService, OrderArchiveService, PriceListService, PurchaseLimitService, RegisterService, SecretService, SoftDeleteService, TwoFactorService, WebhookReplayService};
+use rcommerce_core::shipping::{DeliveryScheduler, PickupService, ShippingProviderFactory, TrackingService};
+use rcommerce_core::subscriptions::{BillingEngine, GatewayCharger, SubscriptionPlanService};
+use rcommerce_core::secrets::SecretBox;
 use rcommerce_core::tax::DefaultTaxService;
+use rcommerce_core::media::{LocalStorage, MediaStorage};
 use rcommerce_core::{DigitalProductService, BundleService, FileUploadService};
+use rcommerce_core::events::{EventBus, EventHandler, EventsConfig, NotificationHandler, OutboxRelay, WebhookHandler};
+use rcommerce_core::notification::{InAppFeed, UnsubscribeLinks};
+use rcommerce_core::websocket::{LiveEvents, WebSocketConfig};
 
-use crate::middleware::AuthRateLimiter;
+use crate::middleware::{AuthRateLimiter, TrafficCapture};
 
 /// Parameters for creating AppState
 pub struct AppStateParams {
@@ -27,6 +42,60 @@ pub struct AppStateParams {
     pub tax_service: Arc<DefaultTaxService>,
     pub shipping_factory: Arc<ShippingProviderFactory>,
     pub checkout_service: Arc<CheckoutService>,
+    pub cache_warmup: CacheWarmupConfig,
+    pub response_cache: ResponseCacheConfig,
+    pub capture: CaptureConfig,
+    pub access_log: AccessLogConfig,
+    pub audit: AuditConfig,
+    pub soft_delete: SoftDeleteConfig,
+    pub websocket: WebSocketConfig,
+    pub events: EventBus,
+    pub events_config: EventsConfig,
+    pub formatting: FormattingConfig,
+    pub geoip: GeoIpConfig,
+    pub order_archive: OrderArchiveConfig,
+    pub webhook_replay: WebhookReplayConfig,
+    pub subscription_billing: SubscriptionBillingConfig,
+    pub dunning: DunningConfig,
+    pub stock_adjustments: StockAdjustmentConfig,
+    pub inventory: InventoryConfig,
+    pub fx: FxConfig,
+    pub default_shipping_provider: Option<String>,
+    pub delivery: DeliveryScheduler,
+    pub returns: ReturnsConfig,
+    pub checkout_fields: CheckoutFieldSchema,
+    pub gift_cards: GiftCardsConfig,
+    pub flash_sales: FlashSalesConfig,
+    pub rate_limiting: RateLimitConfig,
+    pub idempotency: IdempotencyConfig,
+    pub tls: TlsConfig,
+    pub secrets: SecretBox,
+    pub email: EmailConfig,
+    pub unsubscribe: UnsubscribeConfig,
+    pub sessions: SessionConfig,
+    pub marketplaces: MarketplaceConfig,
+    pub automation: AutomationConfig,
+    pub purchasing: PurchasingConfig,
+    pub supplier_feeds: SupplierFeedsConfig,
+    pub fulfillment: FulfillmentConfig,
+    pub reports: ReportsConfig,
+    pub hosted_checkout: HostedCheckoutConfig,
+    pub three_d_secure: ThreeDSecureConfig,
+    pub payment_capture: PaymentCaptureConfig,
+    pub payout_reconciliation: PayoutReconciliationConfig,
+    pub wallets: WalletConfig,
+    pub redirects: RedirectsConfig,
+    pub media: MediaConfig,
+    pub media_storage: Arc<dyn MediaStorage>,
+    pub printing: PrintingConfig,
+    pub observability: ObservabilityConfig,
+    pub metrics_endpoint: bool,
+    pub analytics: AnalyticsConfig,
+    pub tracking: TrackingConfig,
+    pub scheduler: JobSchedulerConfig,
+    pub order_numbers: OrderNumberConfig,
+    pub fraud: FraudConfig,
+    pub apple_pay: Option<ApplePayMerchantValidator>,
 }
 
 impl AppStateParams {
@@ -65,8 +134,369 @@ impl AppStateParams {
             tax_service,
             shipping_factory,
             checkout_service,
+            cache_warmup: CacheWarmupConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
+            capture: CaptureConfig::default(),
+            access_log: AccessLogConfig::default(),
+            audit: AuditConfig::default(),
+            soft_delete: SoftDeleteConfig::default(),
+            websocket: WebSocketConfig::default(),
+            events: EventBus::new(&EventsConfig::default()),
+            events_config: EventsConfig::default(),
+            formatting: FormattingConfig::default(),
+            geoip: GeoIpConfig::default(),
+            order_archive: OrderArchiveConfig::default(),
+            webhook_replay: WebhookReplayConfig::default(),
+            subscription_billing: SubscriptionBillingConfig::default(),
+            dunning: DunningConfig::default(),
+            stock_adjustments: StockAdjustmentConfig::default(),
+            inventory: InventoryConfig::default(),
+            fx: FxConfig::default(),
+            default_shipping_provider: None,
+            delivery: DeliveryScheduler::default(),
+            returns: ReturnsConfig::default(),
+            checkout_fields: CheckoutFieldSchema::default(),
+            gift_cards: GiftCardsConfig::default(),
+            flash_sales: FlashSalesConfig::default(),
+            rate_limiting: RateLimitConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            tls: TlsConfig::default(),
+            secrets: SecretBox::disabled(),
+            email: EmailConfig::default(),
+            unsubscribe: UnsubscribeConfig::default(),
+            sessions: SessionConfig::default(),
+            marketplaces: MarketplaceConfig::default(),
+            automation: AutomationConfig::default(),
+            purchasing: PurchasingConfig::default(),
+            supplier_feeds: SupplierFeedsConfig::default(),
+            fulfillment: FulfillmentConfig::default(),
+            reports: ReportsConfig::default(),
+            hosted_checkout: HostedCheckoutConfig::default(),
+            three_d_secure: ThreeDSecureConfig::default(),
+            payment_capture: PaymentCaptureConfig::default(),
+            payout_reconciliation: PayoutReconciliationConfig::default(),
+            wallets: WalletConfig::default(),
+            redirects: RedirectsConfig::default(),
+            media: MediaConfig::default(),
+            media_storage: Arc::new(LocalStorage::new("./uploads", "http://localhost:8080/uploads")),
+            printing: PrintingConfig::default(),
+            observability: ObservabilityConfig::default(),
+            metrics_endpoint: true,
+            analytics: AnalyticsConfig::default(),
+            tracking: TrackingConfig::default(),
+            scheduler: JobSchedulerConfig::default(),
+            order_numbers: OrderNumberConfig::default(),
+            fraud: FraudConfig::default(),
+            apple_pay: None,
         }
     }
+    
+    /// Override the default cache warmup configuration
+    pub fn with_cache_warmup(mut self, cache_warmup: CacheWarmupConfig) -> Self {
+        self.cache_warmup = cache_warmup;
+        self
+    }
+    
+    /// Override the default (disabled) per-route response caching
+    pub fn with_response_cache(mut self, response_cache: ResponseCacheConfig) -> Self {
+        self.response_cache = response_cache;
+        self
+    }
+    
+    /// Override the default (disabled) traffic capture configuration
+    pub fn with_capture(mut self, capture: CaptureConfig) -> Self {
+        self.capture = capture;
+        self
+    }
+    
+    /// Override the default (disabled) access log configuration
+    pub fn with_access_log(mut self, access_log: AccessLogConfig) -> Self {
+        self.access_log = access_log;
+        self
+    }
+    
+    /// Override the default (disabled) audit log configuration
+    pub fn with_audit(mut self, audit: AuditConfig) -> Self {
+        self.audit = audit;
+        self
+    }
+    
+    /// Override the default (30 day) retention of soft-deleted records
+    pub fn with_soft_delete(mut self, soft_delete: SoftDeleteConfig) -> Self {
+        self.soft_delete = soft_delete;
+        self
+    }
+    
+    /// Override the default admin live events (WebSocket) configuration
+    pub fn with_websocket(mut self, websocket: WebSocketConfig) -> Self {
+        self.websocket = websocket;
+        self
+    }
+    
+    /// Use this event bus (shared with services built before the state) and
+    /// its configuration
+    pub fn with_events(mut self, events: EventBus, events_config: EventsConfig) -> Self {
+        self.events = events;
+        self.events_config = events_config;
+        self
+    }
+    
+    /// Override the default (en-US) price display rules
+    pub fn with_formatting(mut self, formatting: FormattingConfig) -> Self {
+        self.formatting = formatting;
+        self
+    }
+    
+    /// Override the default (disabled) IP geolocation configuration
+    pub fn with_geoip(mut self, geoip: GeoIpConfig) -> Self {
+        self.geoip = geoip;
+        self
+    }
+    
+    /// Override the default (disabled) order auto-archival configuration
+    pub fn with_order_archive(mut self, order_archive: OrderArchiveConfig) -> Self {
+        self.order_archive = order_archive;
+        self
+    }
+    
+    /// Override the default webhook replay limits
+    pub fn with_webhook_replay(mut self, webhook_replay: WebhookReplayConfig) -> Self {
+        self.webhook_replay = webhook_replay;
+        self
+    }
+    
+    /// Override the default (disabled) subscription renewal billing configuration
+    pub fn with_subscription_billing(mut self, subscription_billing: SubscriptionBillingConfig) -> Self {
+        self.subscription_billing = subscription_billing;
+        self
+    }
+    
+    /// Override the default payment retry (dunning) schedule
+    pub fn with_dunning(mut self, dunning: DunningConfig) -> Self {
+        self.dunning = dunning;
+        self
+    }
+    
+    /// Override the default stock adjustment approval thresholds and roles
+    pub fn with_stock_adjustments(mut self, stock_adjustments: StockAdjustmentConfig) -> Self {
+        self.stock_adjustments = stock_adjustments;
+        self
+    }
+    
+    /// Override the default stock reservation and alert settings
+    pub fn with_inventory(mut self, inventory: InventoryConfig) -> Self {
+        self.inventory = inventory;
+        self
+    }
+    
+    /// Override the default (manual rates, USD base) currency conversion configuration
+    pub fn with_fx(mut self, fx: FxConfig) -> Self {
+        self.fx = fx;
+        self
+    }
+    
+    /// Prefer this shipping carrier for address validation (otherwise any available one)
+    pub fn with_default_shipping_provider(mut self, provider: impl Into<String>) -> Self {
+        self.default_shipping_provider = Some(provider.into());
+        self
+    }
+    
+    /// Override the default delivery scheduling rules (cutoff, windows, blackout dates)
+    pub fn with_delivery(mut self, delivery: DeliveryScheduler) -> Self {
+        self.delivery = delivery;
+        self
+    }
+    
+    /// Override the default (30-day window) returns policy and return label settings
+    pub fn with_returns(mut self, returns: ReturnsConfig) -> Self {
+        self.returns = returns;
+        self
+    }
+    
+    /// Define custom checkout fields (none by default)
+    pub fn with_checkout_fields(mut self, checkout_fields: CheckoutFieldSchema) -> Self {
+        self.checkout_fields = checkout_fields;
+        self
+    }
+    
+    /// Override the default gift card amounts and expiry
+    pub fn with_gift_cards(mut self, gift_cards: GiftCardsConfig) -> Self {
+        self.gift_cards = gift_cards;
+        self
+    }
+    
+    /// Override the default flash sale token and queue timings
+    pub fn with_flash_sales(mut self, flash_sales: FlashSalesConfig) -> Self {
+        self.flash_sales = flash_sales;
+        self
+    }
+    
+    /// Override the default rate limits (publishable storefront keys)
+    pub fn with_rate_limiting(mut self, rate_limiting: RateLimitConfig) -> Self {
+        self.rate_limiting = rate_limiting;
+        self
+    }
+    
+    /// Override the default idempotency key retention and limits
+    pub fn with_idempotency(mut self, idempotency: IdempotencyConfig) -> Self {
+        self.idempotency = idempotency;
+        self
+    }
+
+    /// Set the TLS config, which decides whether admin routes need a client certificate
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Seal stored secrets (webhook signing and TOTP secrets) with these master keys
+    pub fn with_secrets(mut self, secrets: SecretBox) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Set the email provider settings used to verify bounce and complaint webhooks
+    pub fn with_email(mut self, email: EmailConfig) -> Self {
+        self.email = email;
+        self
+    }
+
+    /// Set the base URL of the unsubscribe links in marketing emails
+    pub fn with_unsubscribe(mut self, unsubscribe: UnsubscribeConfig) -> Self {
+        self.unsubscribe = unsubscribe;
+        self
+    }
+
+    /// Enable cookie sessions for storefronts (needs Redis)
+    pub fn with_sessions(mut self, sessions: SessionConfig) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Configure the marketplaces listings and orders are synced with
+    pub fn with_marketplaces(mut self, marketplaces: MarketplaceConfig) -> Self {
+        self.marketplaces = marketplaces;
+        self
+    }
+
+    /// Configure the automation rule engine
+    pub fn with_automation(mut self, automation: AutomationConfig) -> Self {
+        self.automation = automation;
+        self
+    }
+
+    /// Configure low stock alerts and purchase order drafts
+    pub fn with_purchasing(mut self, purchasing: PurchasingConfig) -> Self {
+        self.purchasing = purchasing;
+        self
+    }
+
+    /// Configure supplier feed pulls and dropship order sending
+    pub fn with_supplier_feeds(mut self, supplier_feeds: SupplierFeedsConfig) -> Self {
+        self.supplier_feeds = supplier_feeds;
+        self
+    }
+
+    /// Configure shipping preferences and backorders
+    pub fn with_fulfillment(mut self, fulfillment: FulfillmentConfig) -> Self {
+        self.fulfillment = fulfillment;
+        self
+    }
+
+    /// Configure scheduled report subscriptions
+    pub fn with_reports(mut self, reports: ReportsConfig) -> Self {
+        self.reports = reports;
+        self
+    }
+
+    /// Configure hosted checkout redirects and expiry
+    pub fn with_hosted_checkout(mut self, hosted_checkout: HostedCheckoutConfig) -> Self {
+        self.hosted_checkout = hosted_checkout;
+        self
+    }
+
+    /// Configure 3-D Secure challenge returns and expiry
+    pub fn with_three_d_secure(mut self, three_d_secure: ThreeDSecureConfig) -> Self {
+        self.three_d_secure = three_d_secure;
+        self
+    }
+
+    /// Configure manual capture, capture on fulfillment and authorization expiry
+    pub fn with_payment_capture(mut self, payment_capture: PaymentCaptureConfig) -> Self {
+        self.payment_capture = payment_capture;
+        self
+    }
+
+    /// Configure importing and matching gateway payouts
+    pub fn with_payout_reconciliation(mut self, payout_reconciliation: PayoutReconciliationConfig) -> Self {
+        self.payout_reconciliation = payout_reconciliation;
+        self
+    }
+
+    /// Configure Apple Pay and Google Pay, with the Apple Pay merchant
+    /// validator when a merchant identity certificate is configured
+    pub fn with_wallets(mut self, wallets: WalletConfig, apple_pay: Option<ApplePayMerchantValidator>) -> Self {
+        self.wallets = wallets;
+        self.apple_pay = apple_pay;
+        self
+    }
+
+    /// Configure the storefront paths old slugs redirect on
+    pub fn with_redirects(mut self, redirects: RedirectsConfig) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    /// Configure product image storage and resizing
+    pub fn with_media(mut self, media: MediaConfig, media_storage: Arc<dyn MediaStorage>) -> Self {
+        self.media = media;
+        self.media_storage = media_storage;
+        self
+    }
+
+    /// Configure printer profiles of print batches
+    pub fn with_printing(mut self, printing: PrintingConfig) -> Self {
+        self.printing = printing;
+        self
+    }
+
+    /// Configure business metrics and alert rules; `metrics_endpoint`
+    /// serves them on `/metrics`
+    pub fn with_observability(mut self, observability: ObservabilityConfig, metrics_endpoint: bool) -> Self {
+        self.observability = observability;
+        self.metrics_endpoint = metrics_endpoint;
+        self
+    }
+
+    /// Configure first-party storefront analytics
+    pub fn with_analytics(mut self, analytics: AnalyticsConfig) -> Self {
+        self.analytics = analytics;
+        self
+    }
+
+    /// Configure carrier tracking webhooks and polling
+    pub fn with_tracking(mut self, tracking: TrackingConfig) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Configure the locks electing the instance that runs background tasks
+    pub fn with_scheduler(mut self, scheduler: JobSchedulerConfig) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Configure the order number sequences
+    pub fn with_order_numbers(mut self, order_numbers: OrderNumberConfig) -> Self {
+        self.order_numbers = order_numbers;
+        self
+    }
+
+    /// Configure fraud screening thresholds and scorers
+    pub fn with_fraud(mut self, fraud: FraudConfig) -> Self {
+        self.fraud = fraud;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -89,7 +519,134 @@ pub struct AppState {
     pub db: Database,
     pub redis: Option<RedisPool>,
     pub auth_rate_limiter: AuthRateLimiter,
+    /// Per-key limiter for publishable storefront keys; None when rate limiting is off
+    pub storefront_rate_limiter: Option<AuthRateLimiter>,
     pub api_key_repository: Arc<PostgresApiKeyRepository>,
+    pub cache_warmer: Arc<CacheWarmer>,
+    pub warmup_state: WarmupState,
+    /// Per-route cache policies and the responses kept in memory
+    pub response_cache: Arc<ResponseCache>,
+    pub traffic_capture: TrafficCapture,
+    pub formatting: Arc<FormattingService>,
+    pub geoip: Arc<GeoIpService>,
+    pub order_archive: Arc<OrderArchiveService<PostgresOrderArchiveRepository>>,
+    pub webhook_replay: Arc<WebhookReplayService<PostgresWebhookReplayRepository>>,
+    pub subscription_plans: Arc<SubscriptionPlanService<PostgresSubscriptionPlanRepository>>,
+    pub subscription_billing: Arc<BillingEngine<PostgresSubscriptionRepository>>,
+    pub stock_adjustments: Arc<StockAdjustmentService<PostgresStockAdjustmentRepository>>,
+    /// Stock levels and the reservations new orders take
+    pub inventory: Arc<InventoryService>,
+    pub purchasing: Arc<PurchasingService<PostgresPurchaseOrderRepository>>,
+    /// Supplier stock and price feeds, and dropship orders
+    pub supplier_feeds: Arc<SupplierFeedService<PostgresSupplierFeedRepository>>,
+    /// Purchase order dispatch, acknowledgments and discrepancies
+    pub po_dispatch: Arc<PurchaseOrderDispatchService<PostgresPoDispatchRepository>>,
+    /// Shipment planning by shipping preference, and backorders
+    pub shipments: Arc<ShipmentService<PostgresShipmentRepository>>,
+    pub registers: Arc<RegisterService<PostgresRegisterRepository>>,
+    pub price_lists: Arc<PriceListService<PostgresPriceListRepository>>,
+    pub addresses: Arc<AddressBookService<PostgresAddressRepository>>,
+    pub addons: Arc<AddonService<PostgresAddonRepository>>,
+    pub delivery: Arc<DeliveryService<PostgresDeliveryRepository>>,
+    pub returns: Arc<ReturnService<PostgresReturnRepository>>,
+    pub roles: Arc<RoleService<PostgresRoleRepository>>,
+    pub checkout_fields: Arc<CheckoutFieldService<PostgresCheckoutFieldRepository>>,
+    pub gift_cards: Arc<GiftCardService<PostgresGiftCardRepository>>,
+    pub flash_sales: Arc<FlashSaleService<PostgresFlashSaleRepository>>,
+    pub exports: Arc<ExportService<PostgresExportRepository>>,
+    pub purchase_limits: Arc<PurchaseLimitService<PostgresPurchaseLimitRepository>>,
+    pub idempotency: Arc<IdempotencyService<PostgresIdempotencyRepository>>,
+    pub access_denials: Arc<PostgresAccessDenialRepository>,
+    /// Buffered access log of API requests; the server writes it periodically
+    pub access_log: Arc<AccessLogService<PostgresAccessLogRepository>>,
+    /// Append-only audit log of API mutations
+    pub audit: Arc<AuditService<PostgresAuditRepository>>,
+    /// Restore and purge of deleted products, customers and orders
+    pub soft_deletes: Arc<SoftDeleteService<PostgresSoftDeleteRepository>>,
+    /// Admin WebSocket settings
+    pub websocket: Arc<WebSocketConfig>,
+    /// Order, payment, low stock and shipment events for admin dashboards
+    pub live_events: LiveEvents,
+    /// New and read in-app notifications, for the notification bell sockets
+    pub in_app_feed: InAppFeed,
+    /// Domain events published by orders, payments, inventory and shipping
+    pub events: EventBus,
+    /// Subscribers of `events` run once per event (webhooks, customer notices)
+    pub event_handlers: Arc<Vec<Arc<dyn EventHandler>>>,
+    /// Publishes events written to the outbox in transactions to `events`
+    pub outbox: Arc<OutboxRelay>,
+    /// Refuse admin requests without a verified TLS client certificate
+    pub admin_requires_client_cert: bool,
+    pub secrets: Arc<SecretService<PostgresSecretRepository>>,
+    /// Email provider settings, for the bounce and complaint webhooks
+    pub email: Arc<EmailConfig>,
+    pub notifications: Arc<PostgresNotificationRepository>,
+    /// Signs and verifies the unsubscribe links in marketing emails
+    pub unsubscribe: Arc<UnsubscribeLinks>,
+    /// Cookie sessions; None unless enabled and Redis is available
+    pub sessions: Option<Arc<AuthSessionStore>>,
+    /// Login sessions and their rotating refresh tokens
+    pub login_sessions: Arc<LoginSessionService<PostgresLoginSessionRepository>>,
+    /// TOTP credentials, backup codes and login challenges
+    pub two_factor: Arc<TwoFactorService<PostgresTwoFactorRepository>>,
+    /// Social login and the provider accounts linked to customers
+    pub oauth: Arc<OAuthService<PostgresOAuthRepository>>,
+    /// Numbers new orders, per channel
+    pub order_numbers: Arc<OrderNumbers>,
+    /// Risk assessments of orders and the fraud review queue
+    pub fraud: Arc<FraudScreen>,
+    /// Marketplace listings and imported marketplace orders
+    pub marketplace: Arc<PostgresMarketplaceRepository>,
+    pub marketplaces: Arc<MarketplaceConfig>,
+    /// Automation rules; the automation job runs them on queued events
+    pub automation: Arc<AutomationEngine<PostgresAutomationRepository>>,
+    /// Report subscriptions; the reports job sends them
+    pub reports: Arc<ReportService<PostgresReportRepository>>,
+    /// Sales analytics over the daily rollups
+    pub sales: Arc<SalesReportService<PostgresSalesReportRepository>>,
+    /// Checkout sessions and payment links; completed by gateway webhooks
+    pub hosted_checkouts: Arc<HostedCheckoutService<PostgresHostedCheckoutRepository>>,
+    /// Payments started through the payments API, with their 3-D Secure challenges
+    pub payment_sessions: Arc<PaymentSessionService<PostgresPaymentSessionRepository>>,
+    /// Captures and voids of authorized payments
+    pub payment_captures: Arc<PaymentCaptureService<PostgresPaymentCaptureRepository>>,
+    /// Gateway payouts matched against recorded payments and refunds
+    pub payouts: Arc<PayoutReconciliationService<PostgresPayoutRepository>>,
+    /// Customers' own orders, saved payment methods and notification preferences
+    pub accounts: Arc<AccountService<PostgresAccountRepository>>,
+    pub wallets: Arc<WalletConfig>,
+    /// Apple Pay merchant sessions; None when the gateway's JS SDK validates the merchant
+    pub apple_pay: Option<Arc<ApplePayMerchantValidator>>,
+    /// Catalog snapshots and promoted changes between instances
+    pub catalog_promotion: Arc<CatalogPromotionService<PostgresCatalogRepository>>,
+    /// Old slugs and manual redirects
+    pub redirects: Arc<RedirectService<PostgresRedirectRepository>>,
+    /// Customer groups and their negotiated prices
+    pub customer_groups: Arc<CustomerGroupService<PostgresCustomerGroupRepository>>,
+    /// Product attributes, their values and listing filters
+    pub attributes: Arc<AttributeService<PostgresAttributeRepository>>,
+    /// Category tree
+    pub categories: Arc<CategoryService<PostgresCategoryRepository>>,
+    /// Manual and smart collections
+    pub collections: Arc<CollectionService<PostgresCollectionRepository>>,
+    /// Product images and their resized variants
+    pub media: Arc<MediaService<PostgresProductImageRepository>>,
+    /// HS codes and origins of products, for duty quotes and customs declarations
+    pub customs: Arc<PostgresCustomsRepository>,
+    /// Batches of shipping labels and packing slips for print stations
+    pub printing: Arc<PrintService<PostgresPrintBatchRepository>>,
+    /// Business metrics; the metric_alerts job checks alert rules on them
+    pub metrics: Arc<MetricsService<PostgresMetricsRepository>>,
+    /// Whether `/metrics` is served (`features.metrics`)
+    pub metrics_endpoint: bool,
+    /// Storefront funnel events and the checkout funnel
+    pub analytics: Arc<AnalyticsService<PostgresAnalyticsRepository>>,
+    /// Carrier tracking updates of shipments
+    pub tracking: Arc<TrackingService<PostgresTrackingRepository>>,
+    /// Pickup locations and the ready-for-pickup / picked-up lifecycle
+    pub pickup: Arc<PickupService<PostgresPickupRepository>>,
+    /// Locks electing the one instance that runs each background task
+    pub locks: Arc<dyn DistributedLock>,
 }
 
 impl AppState {
@@ -97,8 +654,29 @@ impl AppState {
         // Create auth rate limiter: 5 attempts per minute per IP
         let auth_rate_limiter = AuthRateLimiter::new(5, 60);
         
+        // Create storefront key rate limiter: per publishable key per minute
+        let storefront_rate_limiter = params.rate_limiting.enabled.then(|| {
+            AuthRateLimiter::new(params.rate_limiting.publishable_key_requests_per_minute, 60)
+        });
+        
         // Create subscription service
-        let subscription_service = SubscriptionService::new(params.subscription_repository.clone());
+        let dunning = rcommerce_core::models::DunningConfig::from(&params.dunning);
+        let subscription_service = SubscriptionService::with_dunning_config(
+            params.subscription_repository.clone(),
+            dunning.clone(),
+        );
+        let payment_service = Arc::new(params.payment_service);
+        
+        // Create subscription plans and the renewal engine; the periodic run is spawned by the server
+        let subscription_plans = Arc::new(SubscriptionPlanService::new(
+            PostgresSubscriptionPlanRepository::new(params.db.pool().clone()),
+        ));
+        let subscription_billing = Arc::new(BillingEngine::new(
+            params.subscription_repository.clone(),
+            Arc::new(GatewayCharger::new(payment_service.clone(), params.db.pool().clone())),
+            params.subscription_billing,
+            dunning,
+        ));
         
         // Create digital product service
         let file_upload_service = Arc::new(params.file_upload_service);
@@ -110,6 +688,371 @@ impl AppState {
         // Create bundle service
         let bundle_service = Arc::new(BundleService::new(params.db.clone()));
         
+        // Create cache warmer; readiness stays false until the first warmup finishes
+        let cache_warmer = Arc::new(CacheWarmer::new(
+            params.db.pool().clone(),
+            params.redis.clone(),
+            params.cache_warmup,
+        ));
+        
+        // Create order archiver; the periodic run is spawned by the server
+        let order_archive = Arc::new(OrderArchiveService::new(
+            PostgresOrderArchiveRepository::new(params.db.pool().clone()),
+            params.order_archive,
+        ));
+        
+        // Create webhook replayer; replays run as background tasks
+        let webhook_replay = Arc::new(WebhookReplayService::new(
+            PostgresWebhookReplayRepository::new(params.db.pool().clone()),
+            params.webhook_replay,
+        ).with_secrets(params.secrets.clone()));
+        
+        // Create stock adjustment approvals; approvers are notified through the notification queue
+        let stock_adjustments = Arc::new(
+            StockAdjustmentService::new(
+                PostgresStockAdjustmentRepository::new(params.db.pool().clone()),
+                params.stock_adjustments,
+            )
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        let inventory = Arc::new(InventoryService::new(params.db.clone(), params.inventory));
+        
+        // Create suppliers and purchase orders; low stock alerts go through the notification queue
+        let purchasing = Arc::new(
+            PurchasingService::new(
+                PostgresPurchaseOrderRepository::new(params.db.pool().clone()),
+                params.purchasing.clone(),
+            )
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone())))
+            .with_events(params.events.clone()),
+        );
+        
+        // Create purchase order dispatch; emailed orders and purchasing alerts go through the notification queue
+        let po_dispatch = Arc::new(
+            PurchaseOrderDispatchService::new(
+                PostgresPoDispatchRepository::new(params.db.pool().clone()),
+                params.purchasing,
+            )
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        // Create supplier feeds; dropship orders without an order webhook are emailed through the notification queue
+        let supplier_feeds = Arc::new(
+            SupplierFeedService::new(
+                PostgresSupplierFeedRepository::new(params.db.pool().clone()),
+                params.supplier_feeds,
+            )
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        // Create payment captures; with manual capture, shipments capture their
+        // orders' authorizations on fulfillment
+        let payment_captures = Arc::new(PaymentCaptureService::new(
+            PostgresPaymentCaptureRepository::new(params.db.pool().clone()),
+            payment_service.clone(),
+            params.payment_capture.clone(),
+        ));
+        
+        // Create the event bus subscribers; the server spawns them
+        let mut event_handlers: Vec<Arc<dyn EventHandler>> = Vec::new();
+        if params.events_config.webhooks {
+            event_handlers.push(Arc::new(
+                WebhookHandler::new(
+                    Arc::new(PostgresWebhookDispatchRepository::new(params.db.pool().clone())),
+                    &params.events_config,
+                )
+                .with_secrets(params.secrets.clone()),
+            ));
+        }
+        if params.fulfillment.notify_customers {
+            event_handlers.push(Arc::new(NotificationHandler::new(Arc::new(
+                PostgresNotificationRepository::new(params.db.pool().clone()),
+            ))));
+        }
+        if params.payment_capture.method == CaptureMethod::Manual {
+            event_handlers.push(Arc::new(CaptureOnShipment::new(payment_captures.clone())));
+        }
+        
+        // Create the outbox relay; each instance runs one
+        let outbox = Arc::new(OutboxRelay::new(
+            Arc::new(PostgresOutboxRepository::new(params.db.pool().clone())),
+            params.events.clone(),
+            params.events_config.outbox.clone(),
+        ));
+        
+        // Create carrier tracking; shipment moves are published, and the notification
+        // handler queues the shipped and delivered emails
+        let tracking = Arc::new(
+            TrackingService::new(
+                PostgresTrackingRepository::new(params.db.pool().clone()),
+                params.shipping_factory.clone(),
+                params.tracking,
+            )
+            .with_events(params.events.clone()),
+        );
+        
+        // Create local pickup; ready and picked-up emails go through the notification queue
+        let mut pickup = PickupService::new(PostgresPickupRepository::new(params.db.pool().clone()));
+        if params.fulfillment.notify_customers {
+            pickup = pickup.with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone())));
+        }
+        let pickup = Arc::new(pickup);
+        
+        let locks = locks_from_config(&params.scheduler, params.db.pool().clone(), params.redis.clone());
+        
+        // Create shipment planning; split and backorder emails go through the notification queue,
+        // labels are bought on the carrier account of the shipment's location and kept in media storage
+        let shipments = Arc::new(
+            ShipmentService::new(
+                PostgresShipmentRepository::new(params.db.pool().clone()),
+                params.fulfillment,
+            )
+            .with_labels(params.shipping_factory.clone(), params.default_shipping_provider.clone())
+            .with_label_storage(params.media_storage.clone())
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        // Create POS register sessions
+        let registers = Arc::new(RegisterService::new(PostgresRegisterRepository::new(params.db.pool().clone())));
+        
+        // Create price lists with the configured FX rate provider
+        let price_lists = Arc::new(PriceListService::new(
+            PostgresPriceListRepository::new(params.db.pool().clone()),
+            rcommerce_core::fx::provider_from_config(&params.fx, params.db.pool().clone()),
+            params.fx.base_currency(),
+        ));
+        
+        // Create customer address books, validated with the shipping carriers
+        let addresses = Arc::new(
+            AddressBookService::new(PostgresAddressRepository::new(params.db.pool().clone()))
+                .with_validation(params.shipping_factory.clone(), params.default_shipping_provider.clone()),
+        );
+        
+        // Create the checkout add-on catalog
+        let addons = Arc::new(AddonService::new(PostgresAddonRepository::new(params.db.pool().clone())));
+        
+        // Create delivery scheduling, offering windows for carrier services that support them
+        let delivery = Arc::new(
+            DeliveryService::new(PostgresDeliveryRepository::new(params.db.pool().clone()), params.delivery)
+                .with_shipping(params.shipping_factory.clone()),
+        );
+        
+        // Create returns: labels through the shipping carriers, refunds through the payment gateways
+        let formatting = Arc::new(FormattingService::new(&params.formatting));
+        let returns = Arc::new(
+            ReturnService::new(PostgresReturnRepository::new(params.db.pool().clone()), params.returns)
+                .with_labels(params.shipping_factory.clone(), params.default_shipping_provider.clone())
+                .with_refunds(Arc::new(GatewayRefunder::new(payment_service.clone())))
+                .with_notifications(
+                    Arc::new(PostgresNotificationRepository::new(params.db.pool().clone())),
+                    formatting.clone(),
+                ),
+        );
+        
+        // Create staff roles; admin routes check them on every request
+        let roles = Arc::new(RoleService::new(PostgresRoleRepository::new(params.db.pool().clone())));
+        
+        // Create custom checkout fields for the storefront schema and order admin
+        let checkout_fields = Arc::new(CheckoutFieldService::new(
+            PostgresCheckoutFieldRepository::new(params.db.pool().clone()),
+            params.checkout_fields,
+        ));
+        
+        // Create gift cards; they are spent through the checkout service, expiry is spawned by the server
+        let gift_cards = Arc::new(GiftCardService::new(
+            PostgresGiftCardRepository::new(params.db.pool().clone()),
+            params.gift_cards,
+        ));
+        
+        // Create flash sales; they can only be started with Redis, which holds their live counters
+        let mut flash_sales = FlashSaleService::new(
+            PostgresFlashSaleRepository::new(params.db.pool().clone()),
+            params.flash_sales.clone(),
+        );
+        if let Some(redis) = &params.redis {
+            flash_sales = flash_sales.with_store(FlashSaleStore::new(redis.clone(), &params.flash_sales));
+        }
+        let flash_sales = Arc::new(flash_sales);
+        
+        // Create data exports, streamed to the client as they are read
+        let exports = Arc::new(ExportService::new(PostgresExportRepository::new(params.db.pool().clone())));
+        
+        // Create purchase limit admin; the cart and checkout services enforce the rules
+        let purchase_limits = Arc::new(PurchaseLimitService::new(PostgresPurchaseLimitRepository::new(params.db.pool().clone())));
+        
+        // Create idempotency keys for POST retries; expired keys are purged by the server
+        let idempotency = Arc::new(IdempotencyService::new(
+            PostgresIdempotencyRepository::new(params.db.pool().clone()),
+            params.idempotency,
+        ));
+        
+        // Create the audit log of requests refused by mTLS and API key IP allowlists
+        let access_denials = Arc::new(PostgresAccessDenialRepository::new(params.db.pool().clone()));
+        let admin_requires_client_cert = params.tls.admin_requires_client_cert();
+        
+        // Create the access log for debugging partner integrations; old entries are purged by a recurring job
+        let access_log = Arc::new(AccessLogService::new(
+            PostgresAccessLogRepository::new(params.db.pool().clone()),
+            params.access_log,
+        ));
+        
+        // Create the audit log of API mutations for compliance (who changed prices, refunded orders)
+        let audit = Arc::new(AuditService::new(
+            PostgresAuditRepository::new(params.db.pool().clone()),
+            params.audit,
+        ));
+        
+        // Create soft deletes, restorable until purged after the retention period
+        let soft_deletes = Arc::new(SoftDeleteService::new(
+            PostgresSoftDeleteRepository::new(params.db.pool().clone()),
+            params.soft_delete,
+        ));
+        
+        // Create the fan-out of bus events to admin WebSockets
+        let live_events = LiveEvents::new(params.websocket.broadcast_buffer_size);
+        let in_app_feed = InAppFeed::new(params.websocket.broadcast_buffer_size);
+        
+        // Create envelope encryption for secrets stored in the database
+        let secrets = Arc::new(SecretService::new(PostgresSecretRepository::new(params.db.pool().clone()), params.secrets));
+        
+        // Create the notification store that email provider webhooks update
+        let notifications = Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()));
+        
+        // Sign unsubscribe links with a key derived from the JWT secret
+        let unsubscribe = Arc::new(UnsubscribeLinks::new(
+            &params.auth_service.jwt_config().secret,
+            params.unsubscribe.public_url.clone(),
+            chrono::Duration::days(params.unsubscribe.link_ttl_days),
+        ));
+        
+        // Create login sessions for bearer token clients; expired sessions are purged by the server
+        let login_sessions = Arc::new(LoginSessionService::new(
+            PostgresLoginSessionRepository::new(params.db.pool().clone()),
+            params.auth_service.jwt_config().clone(),
+        ));
+        
+        // Create two-factor authentication; TOTP secrets are sealed like webhook secrets
+        let two_factor = Arc::new(TwoFactorService::new(
+            PostgresTwoFactorRepository::new(params.db.pool().clone()),
+            secrets.secrets().clone(),
+            params.auth_service.two_factor_config().clone(),
+        ));
+        
+        // Create social login with the providers enabled in config
+        let oauth = Arc::new(OAuthService::new(
+            PostgresOAuthRepository::new(params.db.pool().clone()),
+            params.auth_service.oauth_config().clone(),
+        ));
+        
+        // Create the cookie session store for storefronts that don't use bearer tokens
+        let sessions = match (params.sessions.enabled, &params.redis) {
+            (true, Some(redis)) => Some(Arc::new(AuthSessionStore::new(redis.clone(), params.sessions.clone()))),
+            (true, None) => {
+                tracing::warn!("Cookie sessions are enabled but Redis is not available; only bearer tokens will work");
+                None
+            }
+            (false, _) => None,
+        };
+        
+        // Create the marketplace listing store; the marketplace_sync job pushes and imports
+        let order_numbers = Arc::new(OrderNumbers::new(params.order_numbers));
+        let fraud = Arc::new(FraudScreen::new(
+            Arc::new(PostgresFraudRepository::new(params.db.pool().clone())),
+            params.fraud,
+        ));
+        let marketplace = Arc::new(
+            PostgresMarketplaceRepository::new(params.db.pool().clone()).with_order_numbers(order_numbers.clone()),
+        );
+        
+        // Create the automation rule engine
+        let automation = Arc::new(AutomationEngine::new(
+            PostgresAutomationRepository::new(params.db.pool().clone()),
+            params.automation,
+        ));
+        
+        // Create sales analytics; the rollups are refreshed by the sales_views_refresh job
+        let sales = Arc::new(SalesReportService::new(
+            PostgresSalesReportRepository::new(params.db.pool().clone()).with_read_replicas(params.db.clone()),
+            params.reports.clone(),
+        ));
+        
+        // Create report subscriptions; report emails go through the notification queue
+        let reports = Arc::new(
+            ReportService::new(
+                PostgresReportRepository::new(params.db.pool().clone()).with_read_replicas(params.db.clone()),
+                params.reports,
+            )
+            .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        // Create hosted checkouts (Stripe Checkout and Payment Links)
+        let hosted_checkouts = Arc::new(
+            HostedCheckoutService::new(
+                PostgresHostedCheckoutRepository::new(params.db.pool().clone()),
+                payment_service.clone(),
+                params.hosted_checkout,
+            )
+            .with_events(params.events.clone()),
+        );
+        
+        let payment_sessions = Arc::new(
+            PaymentSessionService::new(
+                PostgresPaymentSessionRepository::new(params.db.pool().clone()),
+                payment_service.clone(),
+                params.three_d_secure,
+            )
+            .with_capture(params.payment_capture),
+        );
+        
+        let payouts = Arc::new(PayoutReconciliationService::new(
+            PostgresPayoutRepository::new(params.db.pool().clone()),
+            payment_service.clone(),
+            params.payout_reconciliation,
+        ));
+        
+        let accounts = Arc::new(AccountService::new(
+            PostgresAccountRepository::new(params.db.pool().clone()),
+            payment_service.clone(),
+        ));
+        
+        let catalog_promotion = Arc::new(CatalogPromotionService::new(PostgresCatalogRepository::new(
+            params.db.pool().clone(),
+        )));
+        let redirects = Arc::new(RedirectService::new(
+            PostgresRedirectRepository::new(params.db.pool().clone()),
+            params.redirects,
+        ));
+        let customer_groups = Arc::new(CustomerGroupService::new(PostgresCustomerGroupRepository::new(
+            params.db.pool().clone(),
+        )));
+        let attributes = Arc::new(AttributeService::new(PostgresAttributeRepository::new(params.db.pool().clone())));
+        let categories = Arc::new(CategoryService::new(PostgresCategoryRepository::new(params.db.pool().clone())));
+        let collections = Arc::new(CollectionService::new(PostgresCollectionRepository::new(
+            params.db.pool().clone(),
+        )));
+        let media = Arc::new(MediaService::new(
+            PostgresProductImageRepository::new(params.db.pool().clone()),
+            params.media_storage,
+            params.media,
+        ));
+        // Create business metrics; alert emails go through the notification queue
+        let customs = Arc::new(PostgresCustomsRepository::new(params.db.pool().clone()));
+        let printing = Arc::new(PrintService::new(
+            PostgresPrintBatchRepository::new(params.db.pool().clone()),
+            params.printing,
+        ));
+        
+        let metrics = Arc::new(
+            MetricsService::new(PostgresMetricsRepository::new(params.db.pool().clone()), params.observability)
+                .with_notifications(Arc::new(PostgresNotificationRepository::new(params.db.pool().clone()))),
+        );
+        
+        let analytics = Arc::new(AnalyticsService::new(
+            PostgresAnalyticsRepository::new(params.db.pool().clone()),
+            params.analytics,
+        ));
+        
         Self {
             product_service: params.product_service,
             customer_service: params.customer_service,
@@ -117,7 +1060,7 @@ impl AppState {
             subscription_service,
             subscription_repository: Arc::new(params.subscription_repository),
             coupon_service: params.coupon_service,
-            payment_service: Arc::new(params.payment_service),
+            payment_service,
             digital_product_service,
             bundle_service,
             file_upload_service,
@@ -129,7 +1072,93 @@ impl AppState {
             db: params.db,
             redis: params.redis,
             auth_rate_limiter,
+            storefront_rate_limiter,
             api_key_repository: Arc::new(params.api_key_repository),
+            cache_warmer,
+            warmup_state: WarmupState::new(),
+            response_cache: Arc::new(ResponseCache::new(params.response_cache)),
+            traffic_capture: TrafficCapture::new(params.capture),
+            geoip: Arc::new(GeoIpService::new(params.geoip, &params.formatting.default_locale)),
+            formatting,
+            order_archive,
+            webhook_replay,
+            subscription_plans,
+            subscription_billing,
+            stock_adjustments,
+            inventory,
+            purchasing,
+            supplier_feeds,
+            po_dispatch,
+            shipments,
+            registers,
+            price_lists,
+            addresses,
+            addons,
+            delivery,
+            returns,
+            roles,
+            checkout_fields,
+            gift_cards,
+            flash_sales,
+            exports,
+            purchase_limits,
+            idempotency,
+            access_denials,
+            access_log,
+            audit,
+            soft_deletes,
+            websocket: Arc::new(params.websocket),
+            live_events,
+            in_app_feed,
+            events: params.events,
+            event_handlers: Arc::new(event_handlers),
+            outbox,
+            admin_requires_client_cert,
+            secrets,
+            email: Arc::new(params.email),
+            notifications,
+            unsubscribe,
+            sessions,
+            login_sessions,
+            two_factor,
+            oauth,
+            order_numbers,
+            fraud,
+            marketplace,
+            marketplaces: Arc::new(params.marketplaces),
+            automation,
+            reports,
+            sales,
+            hosted_checkouts,
+            payment_sessions,
+            payment_captures,
+            payouts,
+            accounts,
+            wallets: Arc::new(params.wallets),
+            apple_pay: params.apple_pay.map(Arc::new),
+            catalog_promotion,
+            redirects,
+            customer_groups,
+            attributes,
+            categories,
+            collections,
+            media,
+            customs,
+            printing,
+            metrics,
+            metrics_endpoint: params.metrics_endpoint,
+            analytics,
+            tracking,
+            pickup,
+            locks,
         }
     }
+
+    /// Verify a bearer access token; tokens of revoked or expired login
+    /// sessions are refused with `Error::Unauthorized`
+    pub async fn verify_access_token(&self, token: &str) -> rcommerce_core::Result<JwtClaims> {
+        self.login_sessions
+            .verify_access_token(&self.auth_service, token, chrono::Utc::now())
+            .await
+    }
 }
@@ -0,0 +1,142 @@
+//! Mutual TLS for the manual-certificate HTTPS server
+//!
+//! Clients may present a certificate signed by the configured CA. The
+//! handshake does not require one - storefront and customer traffic share
+//! the listener - so the outcome is attached to every request on the
+//! connection as a [`TlsClientAuth`] extension, and the admin middleware
+//! refuses requests without a verified certificate.
+
+use std::{future::Future, io, path::Path, pin::Pin, sync::Arc};
+
+use axum::Extension;
+use axum_server::{accept::Accept, tls_rustls::{RustlsAcceptor, RustlsConfig}};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tower::Layer;
+
+use rcommerce_core::config::ClientAuthConfig;
+use rcommerce_core::{Error, Result};
+
+/// Client certificate outcome of a TLS connection
+#[derive(Debug, Clone, Default)]
+pub struct TlsClientAuth {
+    /// SHA-256 hex fingerprint of the verified client certificate, if any
+    pub certificate_fingerprint: Option<String>,
+}
+
+impl TlsClientAuth {
+    pub fn is_verified(&self) -> bool {
+        self.certificate_fingerprint.is_some()
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| Error::Config(format!("Failed to read {:?}: {}", path, e)))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = read_pem(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| Error::Config(format!("Failed to parse certificates in {:?}: {}", path, e)))?;
+    if certs.is_empty() {
+        return Err(Error::Config(format!("No certificates found in {:?}", path)));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let pem = read_pem(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|e| Error::Config(format!("Failed to parse private key in {:?}: {}", path, e)))?
+        .ok_or_else(|| Error::Config(format!("No private key found in {:?}", path)))
+}
+
+/// Build a rustls server config that verifies (optional) client certificates
+pub fn server_config(cert_file: &Path, key_file: &Path, client_auth: &ClientAuthConfig) -> Result<RustlsConfig> {
+    let mut roots = RootCertStore::empty();
+    for ca in load_certs(&client_auth.ca_file)? {
+        roots
+            .add(ca)
+            .map_err(|e| Error::Config(format!("Invalid client CA certificate: {}", e)))?;
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .allow_unauthenticated()
+        .build()
+        .map_err(|e| Error::Config(format!("Failed to build client certificate verifier: {}", e)))?;
+
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::Config(format!("Failed to configure TLS: {}", e)))?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(load_certs(cert_file)?, load_key(key_file)?)
+        .map_err(|e| Error::Config(format!("Failed to load TLS certificates: {}", e)))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+/// SHA-256 hex fingerprint of a DER certificate
+pub fn certificate_fingerprint(der: &[u8]) -> String {
+    hex::encode(Sha256::digest(der))
+}
+
+/// TLS acceptor that attaches the client certificate outcome to each request
+#[derive(Clone)]
+pub struct ClientAuthAcceptor {
+    inner: RustlsAcceptor,
+}
+
+impl ClientAuthAcceptor {
+    pub fn new(config: RustlsConfig) -> Self {
+        Self { inner: RustlsAcceptor::new(config) }
+    }
+}
+
+impl<I, S> Accept<I, S> for ClientAuthAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = axum::middleware::AddExtension<S, TlsClientAuth>;
+    type Future = Pin<Box<dyn Future<Output = io::Result<(Self::Stream, Self::Service)>> + Send>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let handshake = self.inner.accept(stream, service);
+        Box::pin(async move {
+            let (stream, service) = handshake.await?;
+            // Only certificates that chained to the CA get past the handshake
+            let client_auth = TlsClientAuth {
+                certificate_fingerprint: stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|chain| chain.first())
+                    .map(|cert| certificate_fingerprint(cert.as_ref())),
+            };
+            Ok((stream, Extension(client_auth).layer(service)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_certificate_fingerprint() {
+        let fingerprint = certificate_fingerprint(b"certificate");
+        assert_eq!(fingerprint.len(), 64);
+        assert_ne!(fingerprint, certificate_fingerprint(b"other"));
+        assert!(!TlsClientAuth::default().is_verified());
+    }
+}
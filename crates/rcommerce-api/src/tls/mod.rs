@@ -1,3 +1,4 @@
+pub mod client_auth;
 pub mod config;
 
 #[cfg(feature = "letsencrypt")]
@@ -10,6 +11,7 @@ use axum::{
 };
 
 // Re-export only the types that don't conflict with rcommerce_core
+pub use client_auth::{ClientAuthAcceptor, TlsClientAuth};
 pub use config::{HstsConfig, TlsVersion};
 
 #[cfg(feature = "letsencrypt")]
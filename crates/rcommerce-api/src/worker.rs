@@ -0,0 +1,64 @@
+//! Worker process: recurring jobs and background tasks without HTTP
+//!
+//! `rcommerce worker` runs what `rcommerce server` runs in the background
+//! (recurring jobs such as subscription renewals, report emails and
+//! marketplace and supplier feed imports, plus archiving and purges)
+//! without binding a port, so web and job processing scale separately. Set
+//! `scheduler.in_api_server = false` so the API servers leave the work to
+//! the workers. On SIGTERM or Ctrl-C a worker stops taking jobs and waits
+//! up to `scheduler.drain_timeout_secs` for running ones.
+
+use std::sync::Arc;
+
+use tracing::info;
+
+use rcommerce_core::{Config, Error, Result};
+
+/// Run the worker until SIGTERM or Ctrl-C
+pub async fn run(config: Config) -> Result<()> {
+    if !config.scheduler.enabled {
+        return Err(Error::Config(
+            "scheduler.enabled is false, so a worker has nothing to run".to_string(),
+        ));
+    }
+
+    let app_state = crate::server::create_app_state(&config).await?;
+    crate::server::spawn_event_handlers(&app_state);
+    crate::routes::notification_template::spawn_theme_reload(&app_state);
+    crate::server::spawn_background_tasks(&app_state, &config);
+    let Some(scheduler) = crate::scheduler::build(&app_state, &config.scheduler) else {
+        info!("Worker running background tasks only; waiting for shutdown");
+        shutdown_signal().await;
+        return Ok(());
+    };
+
+    info!(
+        "R Commerce worker started (at most {} jobs at once, {}s drain timeout)",
+        config.scheduler.concurrency, config.scheduler.drain_timeout_secs
+    );
+    Arc::new(scheduler).run_until(shutdown_signal()).await?;
+    info!("Worker stopped");
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Ctrl-C received, stopping the worker"),
+        _ = terminate => info!("SIGTERM received, stopping the worker"),
+    }
+}
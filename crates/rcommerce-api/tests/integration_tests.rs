@@ -105,6 +105,11 @@ pub struct TestApp {
 impl TestApp {
     /// Create a new test application with all services initialized
     pub async fn new() -> anyhow::Result<Self> {
+        Self::with_state(|_| {}).await
+    }
+    
+    /// Like `new`, adjusting the app state before the server starts
+    pub async fn with_state(configure: impl FnOnce(&mut AppState)) -> anyhow::Result<Self> {
         let config = TestConfig::default();
         
         // Initialize tracing
@@ -237,7 +242,8 @@ impl TestApp {
             checkout_service,
         );
         
-        let app_state = AppState::new(params);
+        let mut app_state = AppState::new(params);
+        configure(&mut app_state);
         
         // Create HTTP client with proxy disabled for localhost
         let http_client = reqwest::Client::builder()
@@ -393,23 +399,18 @@ impl TestApp {
             .await
             .ok(); // Ignore errors - schema might not exist yet
         
-        // Define migration files in order
-        // Paths are relative to the test crate root (crates/rcommerce-api/)
-        let migration_files = [
-            "../rcommerce-core/migrations/001_complete_schema.sql",
-            "../rcommerce-core/migrations/002_tax_system.sql",
-            "../rcommerce-core/migrations/003_inventory_notifications_fulfillment.sql",
-        ];
-        
-        for migration_file in &migration_files {
-            let migration_sql = std::fs::read_to_string(migration_file)
-                .map_err(|e| anyhow::anyhow!("Failed to read migration file {}: {}", migration_file, e))?;
-            
-            sqlx::raw_sql(&migration_sql)
-                .execute(pool)
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to run migration {}: {}", migration_file, e))?;
-        }
+        // The registered migrations, then the optional tax schema (002)
+        rcommerce_core::Migrator::new(pool.clone())
+            .migrate()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to run migrations: {}", e))?;
+        let migration_file = "../rcommerce-core/migrations/002_tax_system.sql";
+        let migration_sql = std::fs::read_to_string(migration_file)
+            .map_err(|e| anyhow::anyhow!("Failed to read migration file {}: {}", migration_file, e))?;
+        sqlx::raw_sql(&migration_sql)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to run migration {}: {}", migration_file, e))?;
         
         Ok(())
     }
@@ -1074,6 +1075,45 @@ async fn test_product_listing() {
     app.cleanup().await.ok();
 }
 
+/// Test 7b: The streamed product listing is one complete JSON array
+#[tokio::test]
+async fn test_product_stream() {
+    let app = TestApp::new().await.expect("Failed to create test app");
+    let (customer, password) = app.create_test_customer().await.expect("Failed to create customer");
+    let token = app.login(&customer.email, &password).await.expect("Failed to login");
+    
+    // Three products of one vendor, read one per query so the body spans several chunks
+    let vendor = format!("stream-vendor-{}", Uuid::new_v4());
+    let mut expected = Vec::new();
+    for title in ["Streamed Product 1", "Streamed Product 2", "Streamed Product 3"] {
+        let product_id = app.create_test_product(title, Decimal::new(1000, 2), 10).await.unwrap();
+        sqlx::query("UPDATE products SET vendor = $1 WHERE id = $2")
+            .bind(&vendor)
+            .bind(product_id)
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+        expected.push(product_id.to_string());
+    }
+    
+    let response = app.http_client
+        .get(format!("{}/api/v1/products/stream?vendor={}&per_page=1", app.base_url(), vendor))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to stream products");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    
+    let body = response.bytes().await.expect("Failed to read streamed body");
+    let products: Vec<serde_json::Value> = serde_json::from_slice(&body).expect("Streamed body is not a JSON array");
+    let mut ids: Vec<String> = products.iter().map(|p| p["id"].as_str().unwrap().to_string()).collect();
+    ids.sort();
+    expected.sort();
+    assert_eq!(ids, expected);
+    
+    app.cleanup().await.ok();
+}
+
 /// Test 8: Order creation and retrieval
 #[tokio::test]
 async fn test_order_creation() {
@@ -1132,6 +1172,135 @@ async fn test_order_creation() {
     app.cleanup().await.ok();
 }
 
+/// Test 8a: Customers can't create orders priced for another customer
+#[tokio::test]
+async fn test_order_for_another_customer_is_forbidden() {
+    let app = TestApp::new().await.expect("Failed to create test app");
+    let (customer, password) = app.create_test_customer().await.expect("Failed to create customer");
+    let (other, _) = app.create_test_customer().await.expect("Failed to create customer");
+    let token = app.login(&customer.email, &password).await.expect("Failed to login");
+    let product_id = app.create_test_product("Group Priced Product", Decimal::new(1500, 2), 10).await.unwrap();
+    
+    let order = |customer_id: Uuid| {
+        app.http_client
+            .post(format!("{}/api/v1/orders", app.base_url()))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({
+                "customer_id": customer_id,
+                "customer_email": customer.email,
+                "items": [{ "product_id": product_id, "quantity": 1 }]
+            }))
+            .send()
+    };
+    
+    let response = order(other.id).await.expect("Failed to create order");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    
+    let response = order(customer.id).await.expect("Failed to create order");
+    assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+    
+    app.cleanup().await.ok();
+}
+
+/// Test 8b: Orders of managed products without stock levels take their
+/// stock from the product's inventory quantity
+#[tokio::test]
+async fn test_order_product_without_stock_levels() {
+    let app = TestApp::new().await.expect("Failed to create test app");
+    let (customer, password) = app.create_test_customer().await.expect("Failed to create customer");
+    let token = app.login(&customer.email, &password).await.expect("Failed to login");
+    let product_id = app.create_test_product("Unlevelled Product", Decimal::new(1500, 2), 5).await.unwrap();
+    
+    let order = |quantity: i32| {
+        app.http_client
+            .post(format!("{}/api/v1/orders", app.base_url()))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({
+                "customer_id": customer.id,
+                "customer_email": customer.email,
+                "items": [{ "product_id": product_id, "quantity": quantity }]
+            }))
+            .send()
+    };
+    let inventory = || {
+        sqlx::query_scalar::<_, i32>("SELECT inventory_quantity FROM products WHERE id = $1")
+            .bind(product_id)
+            .fetch_one(&app.db_pool)
+    };
+    
+    let response = order(3).await.expect("Failed to create order");
+    assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+    assert_eq!(inventory().await.unwrap(), 2);
+    
+    let response = order(3).await.expect("Failed to create order");
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+    assert_eq!(inventory().await.unwrap(), 2);
+    
+    app.cleanup().await.ok();
+}
+
+/// Test 8c: Requiring staff client certificates only guards admin routes
+#[tokio::test]
+async fn test_admin_client_certificate_requirement() {
+    let app = TestApp::with_state(|state| state.admin_requires_client_cert = true)
+        .await
+        .expect("Failed to create test app");
+    let (customer, password) = app.create_test_customer().await.expect("Failed to create customer");
+    let token = app.login(&customer.email, &password).await.expect("Failed to login");
+    
+    // The test server has no TLS, so no connection presents a certificate
+    let response = app.http_client
+        .get(format!("{}/api/v1/admin/statistics/dashboard", app.base_url()))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to call admin route");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    
+    let response = app.http_client
+        .get(format!("{}/api/v1/customers/me", app.base_url()))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to call customer route");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    
+    app.cleanup().await.ok();
+}
+
+/// Test 8d: Admin routes refuse access tokens of revoked login sessions
+#[tokio::test]
+async fn test_admin_routes_reject_revoked_session() {
+    let app = TestApp::new().await.expect("Failed to create test app");
+    let (customer, password) = app.create_test_customer().await.expect("Failed to create customer");
+    let token = app.login(&customer.email, &password).await.expect("Failed to login");
+    
+    let dashboard = || {
+        app.http_client
+            .get(format!("{}/api/v1/admin/statistics/dashboard", app.base_url()))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    
+    // Authenticated, but a customer may not read admin routes
+    let response = dashboard().await.expect("Failed to call admin route");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    
+    let response = app.http_client
+        .post(format!("{}/api/v1/auth/logout", app.base_url()))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to logout");
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    
+    // The token itself is unexpired, but its session has ended
+    let response = dashboard().await.expect("Failed to call admin route");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    
+    app.cleanup().await.ok();
+}
+
 /// Test 9: Cart item updates
 #[tokio::test]
 async fn test_cart_item_updates() {
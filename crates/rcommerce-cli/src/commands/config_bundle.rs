@@ -0,0 +1,166 @@
+//! Export and import the store configuration
+//!
+//! `config export` writes tax categories, zones and rates, shipping rules,
+//! stored email templates and payment gateway settings (without
+//! credentials) as a versioned YAML bundle, to a file or stdout. `config
+//! import` shows what a bundle changes on this instance and applies it.
+//! Gateway settings live in the config file, so the changes to make there
+//! are listed instead.
+
+use colored::Colorize;
+use dialoguer::Confirm;
+use std::path::Path;
+
+use rcommerce_core::models::{CatalogChangeAction, ConfigBundle};
+use rcommerce_core::repository::{PostgresCatalogRepository, PostgresNotificationTemplateRepository};
+use rcommerce_core::services::ConfigBundleService;
+use rcommerce_core::Config;
+
+async fn service(
+    config: &Config,
+) -> Result<ConfigBundleService<PostgresCatalogRepository, PostgresNotificationTemplateRepository>, String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    Ok(ConfigBundleService::new(
+        PostgresCatalogRepository::new(pool.clone()),
+        PostgresNotificationTemplateRepository::new(pool),
+    ))
+}
+
+/// Bundle as YAML
+pub fn to_yaml(bundle: &ConfigBundle) -> Result<String, String> {
+    serde_yaml::to_string(bundle).map_err(|e| format!("Failed to write bundle: {}", e))
+}
+
+/// Bundle from YAML
+pub fn from_yaml(yaml: &str) -> Result<ConfigBundle, String> {
+    serde_yaml::from_str(yaml).map_err(|e| format!("Invalid configuration bundle: {}", e))
+}
+
+/// Write this instance's configuration bundle
+pub async fn export(config: &Config, output: Option<&Path>) -> Result<(), String> {
+    let bundle = service(config).await?.export(&config.payment).await.map_err(|e| e.to_string())?;
+    let yaml = to_yaml(&bundle)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, yaml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            eprintln!(
+                "{}",
+                format!(
+                    "✅ Exported {} tax zones, {} tax rates, {} shipping rules and {} email templates to {}",
+                    bundle.tax_zones.len(),
+                    bundle.tax_rates.len(),
+                    bundle.shipping_rules.len(),
+                    bundle.email_templates.len(),
+                    path.display()
+                )
+                .green()
+                .bold()
+            );
+        }
+        None => print!("{}", yaml),
+    }
+    Ok(())
+}
+
+/// Apply a configuration bundle to this instance
+pub async fn import(config: &Config, file: &Path, include_deletes: bool, dry_run: bool, yes: bool) -> Result<(), String> {
+    let yaml = std::fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let bundle = from_yaml(&yaml)?;
+    let service = service(config).await?;
+    let plan = service
+        .plan(&bundle, &config.payment, include_deletes)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !plan.payment_differences.is_empty() {
+        println!("{}", "Payment settings to change in the config file:".bold());
+        for difference in &plan.payment_differences {
+            println!("  {}", difference.yellow());
+        }
+    }
+    if plan.is_empty() {
+        println!("{}", format!("✓ Configuration already matches {}", file.display()).green());
+        return Ok(());
+    }
+
+    println!("{}", format!("{} changes to import from {}:", plan.changes.len() + plan.email_templates.len(), file.display()).bold());
+    for change in &plan.changes {
+        let line = super::promote::describe(change);
+        match change.action {
+            CatalogChangeAction::Create => println!("  {}", line.green()),
+            CatalogChangeAction::Update => println!("  {}", line.yellow()),
+            CatalogChangeAction::Delete => println!("  {}", line.red()),
+        }
+    }
+    for name in &plan.email_templates {
+        println!("  {}", format!("~ email_template {}", name).yellow());
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+    if !yes {
+        let confirmed = Confirm::new()
+            .with_prompt("Apply these changes?")
+            .default(false)
+            .interact()
+            .map_err(|e| format!("Failed to read confirmation: {}", e))?;
+        if !confirmed {
+            println!("Import cancelled");
+            return Ok(());
+        }
+    }
+
+    let result = service.import(&bundle, &plan).await.map_err(|e| e.to_string())?;
+    println!(
+        "{}",
+        format!(
+            "✓ Imported configuration: {} created, {} updated, {} deleted, {} email templates",
+            result.created, result.updated, result.deleted, result.email_templates
+        )
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcommerce_core::models::{CatalogTaxZone, PaymentSettings, CONFIG_BUNDLE_VERSION};
+    use rcommerce_core::shipping::{RuleAction, RuleCondition, ShippingRule};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_bundle_yaml_round_trip() {
+        let bundle = ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            tax_zones: vec![CatalogTaxZone {
+                code: "DE".to_string(),
+                name: "Germany".to_string(),
+                country_code: "DE".to_string(),
+                region_code: None,
+                postal_code_pattern: None,
+                zone_type: "country".to_string(),
+                parent_code: None,
+            }],
+            shipping_rules: vec![ShippingRule::new(
+                "free over 100",
+                RuleCondition::All(vec![
+                    RuleCondition::DestinationCountry { countries: vec!["DE".to_string()] },
+                    RuleCondition::OrderTotal { min: Some(Decimal::from(100)), max: None },
+                ]),
+                RuleAction::FreeShipping,
+            )],
+            payment: PaymentSettings::from_config(&Default::default()),
+            ..ConfigBundle::default()
+        };
+
+        let yaml = to_yaml(&bundle).unwrap();
+        assert!(yaml.starts_with("version: 1\n"));
+        assert_eq!(from_yaml(&yaml).unwrap(), bundle);
+        // Exports are byte-for-byte reproducible
+        assert_eq!(to_yaml(&from_yaml(&yaml).unwrap()).unwrap(), yaml);
+    }
+}
@@ -0,0 +1,333 @@
+//! `demo up`: a sample store in one command
+//!
+//! Migrates the database and seeds the demo catalog, creates an API key
+//! for the demo frontend, then runs the API in this process and the
+//! frontend server (`rcommerce-frontend`) as a child process, restarting
+//! it if it exits. Once both answer on `/health` the store's URLs are
+//! printed, the key only by its prefix; Ctrl-C stops both. Each run
+//! revokes the key of the previous one. Meant for trying R Commerce
+//! locally, not for production.
+
+use colored::Colorize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use rcommerce_core::{Config, Migrator};
+
+/// Name of the frontend's API key, to find keys of earlier runs
+const DEMO_KEY_NAME: &str = "Demo frontend (rcommerce demo up)";
+
+/// What the demo frontend needs to browse the catalog and check out
+const DEMO_KEY_SCOPES: &str = "products:read,orders:write,carts:write";
+
+/// Days until the demo key expires
+const DEMO_KEY_DAYS: i64 = 30;
+
+/// How long to wait for the API or the frontend to answer
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Frontend restarts allowed before giving up
+const MAX_FRONTEND_RESTARTS: u32 = 5;
+
+/// Options of `demo up`
+#[derive(Debug, Clone)]
+pub struct DemoOptions {
+    pub api_port: u16,
+    pub frontend_port: u16,
+    /// Frontend server binary; defaults to `rcommerce-frontend` next to
+    /// this binary, else on the PATH
+    pub frontend_bin: Option<PathBuf>,
+    /// Templates and static files of the frontend
+    pub frontend_dir: PathBuf,
+    pub skip_seed: bool,
+    /// Run only the API
+    pub no_frontend: bool,
+}
+
+fn step(message: &str) {
+    println!("{} {}", "→".cyan().bold(), message);
+}
+
+/// Provision the sample store and run it until Ctrl-C
+pub async fn up(mut config: Config, options: DemoOptions) -> Result<(), String> {
+    config.server.host = "127.0.0.1".to_string();
+    config.server.port = options.api_port;
+    config.tls.enabled = false;
+    let api_url = format!("http://127.0.0.1:{}", options.api_port);
+    let frontend_url = format!("http://localhost:{}", options.frontend_port);
+
+    step("Migrating the database");
+    crate::run_migrations(&config)
+        .await
+        .map_err(|e| format!("Database migration failed: {}", e))?;
+    let pool = crate::create_pool(&config).await.map_err(|e| e.to_string())?;
+    if !options.skip_seed {
+        step("Seeding the sample catalog");
+        Migrator::new(pool.clone())
+            .seed()
+            .await
+            .map_err(|e| format!("Seeding failed: {}", e))?;
+    }
+
+    let api_key = if options.no_frontend {
+        None
+    } else {
+        step("Creating the frontend's API key");
+        Some(create_demo_key(&pool, &config).await?)
+    };
+
+    step(&format!("Starting the API on {}", api_url));
+    let mut api = tokio::spawn(rcommerce_api::run(config));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| e.to_string())?;
+    tokio::select! {
+        result = &mut api => {
+            return Err(match result {
+                Ok(Err(e)) => format!("The API stopped: {}", e),
+                _ => "The API stopped".to_string(),
+            });
+        }
+        ready = wait_ready(&client, &format!("{}/health", api_url)) => {
+            if !ready {
+                api.abort();
+                return Err(format!("The API did not answer on {}/health", api_url));
+            }
+        }
+    }
+
+    let frontend = match &api_key {
+        Some(api_key) => {
+            let bin = options.frontend_bin.clone().unwrap_or_else(default_frontend_bin);
+            step(&format!("Starting the frontend ({}) on {}", bin.display(), frontend_url));
+            Some(FrontendProcess {
+                bin,
+                bind: format!("127.0.0.1:{}", options.frontend_port),
+                api_url: api_url.clone(),
+                api_key: api_key.clone(),
+                config_file: write_frontend_config(&options.frontend_dir)?,
+            })
+        }
+        None => None,
+    };
+    let supervisor = async {
+        match &frontend {
+            Some(frontend) => frontend.supervise(&client, &frontend_url, api_key.as_deref()).await,
+            None => {
+                print_ready(&api_url, None, None);
+                std::future::pending::<Result<(), String>>().await
+            }
+        }
+    };
+
+    let result = tokio::select! {
+        result = &mut api => match result {
+            Ok(Err(e)) => Err(format!("The API stopped: {}", e)),
+            _ => Err("The API stopped".to_string()),
+        },
+        result = supervisor => result,
+        _ = tokio::signal::ctrl_c() => {
+            println!();
+            step("Stopping the demo store");
+            Ok(())
+        }
+    };
+    api.abort();
+    result
+}
+
+/// Revoke the keys of earlier runs and create a new one
+async fn create_demo_key(pool: &sqlx::PgPool, config: &Config) -> Result<String, String> {
+    let keys = crate::list_api_keys(pool, None).await.map_err(|e| e.to_string())?;
+    for key in keys.iter().filter(|key| key.name == DEMO_KEY_NAME && key.revoked_at.is_none()) {
+        crate::revoke_api_key(pool, &key.key_prefix, Some("Replaced by rcommerce demo up".to_string()))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let auth_service = rcommerce_core::services::AuthService::new(config.clone());
+    let options = crate::NewApiKey {
+        customer_id: None,
+        name: Some(DEMO_KEY_NAME.to_string()),
+        scopes: DEMO_KEY_SCOPES.to_string(),
+        expires_days: Some(DEMO_KEY_DAYS),
+        publishable: false,
+        origins: Vec::new(),
+        rate_limit: None,
+        allow_ips: Vec::new(),
+    };
+    let (_, full_key) = crate::create_api_key(pool, &auth_service, options)
+        .await
+        .map_err(|e| format!("Failed to create the demo API key: {}", e))?;
+    Ok(full_key)
+}
+
+/// The key's prefix with the secret hidden; the frontend gets the full key
+/// through its environment, and `api-key revoke` takes the prefix
+fn masked_key(full_key: &str) -> String {
+    match full_key.split_once('.') {
+        Some((prefix, _)) => format!("{}.********", prefix),
+        None => "********".to_string(),
+    }
+}
+
+/// A frontend config file serving the demo templates and static files
+/// from `dir`; None if there is no such directory
+fn write_frontend_config(dir: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    if !dir.is_dir() {
+        eprintln!(
+            "{}",
+            format!("⚠️  {} not found, the frontend starts without the demo pages", dir.display()).yellow()
+        );
+        return Ok(None);
+    }
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?;
+    let mut contents = toml::map::Map::new();
+    contents.insert("template_dir".to_string(), dir.display().to_string().into());
+    contents.insert("static_dir".to_string(), dir.display().to_string().into());
+    contents.insert("dev_mode".to_string(), true.into());
+    let path = std::env::temp_dir().join("rcommerce-demo-frontend.toml");
+    std::fs::write(&path, toml::Value::Table(contents).to_string())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+/// `rcommerce-frontend` next to this binary, else the name alone for a PATH lookup
+fn default_frontend_bin() -> PathBuf {
+    let name = format!("rcommerce-frontend{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&name)))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Poll a health URL until it answers 2xx; false on timeout
+async fn wait_ready(client: &reqwest::Client, url: &str) -> bool {
+    let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if let Ok(response) = client.get(url).send().await {
+            if response.status().is_success() {
+                return true;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    false
+}
+
+fn print_ready(api_url: &str, frontend_url: Option<&str>, api_key: Option<&str>) {
+    println!();
+    println!("{}", "✅ The demo store is ready".green().bold());
+    println!();
+    if let Some(frontend_url) = frontend_url {
+        println!("  Storefront:  {}", frontend_url.bright_cyan());
+    }
+    println!("  Products:    {}", format!("{}/api/v1/products", api_url).bright_cyan());
+    println!("  Health:      {}", format!("{}/health", api_url).bright_cyan());
+    if let Some(api_key) = api_key {
+        println!("  API key:     {} (expires in {} days)", masked_key(api_key), DEMO_KEY_DAYS);
+    }
+    println!();
+    println!("Press Ctrl-C to stop.");
+}
+
+/// The frontend server child process
+struct FrontendProcess {
+    bin: PathBuf,
+    bind: String,
+    api_url: String,
+    api_key: String,
+    /// Frontend config pointing at the demo templates and static files
+    config_file: Option<PathBuf>,
+}
+
+impl FrontendProcess {
+    fn spawn(&self) -> Result<tokio::process::Child, String> {
+        let mut command = tokio::process::Command::new(&self.bin);
+        command
+            .arg("--bind")
+            .arg(&self.bind)
+            .arg("--api-url")
+            .arg(&self.api_url)
+            // Passed in the environment so it doesn't show in process lists
+            .env("FRONTEND_API_KEY", &self.api_key)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        if let Some(config) = &self.config_file {
+            command.arg("--config").arg(config);
+        }
+        command.spawn().map_err(|e| {
+            format!(
+                "Failed to start {}: {} (build it with `cargo build -p rcommerce-demo-server` or pass --frontend-bin)",
+                self.bin.display(),
+                e
+            )
+        })
+    }
+
+    /// Run the frontend, restarting it with backoff when it exits; returns
+    /// an error once it has failed too often
+    async fn supervise(&self, client: &reqwest::Client, url: &str, api_key: Option<&str>) -> Result<(), String> {
+        let mut restarts = 0;
+        let mut announced = false;
+        loop {
+            let mut child = self.spawn()?;
+            if !announced {
+                let health = format!("http://{}/health", self.bind);
+                tokio::select! {
+                    status = child.wait() => {
+                        let status = status.map_err(|e| e.to_string())?;
+                        return Err(format!("The frontend exited during startup ({})", status));
+                    }
+                    ready = wait_ready(client, &health) => {
+                        if !ready {
+                            return Err(format!("The frontend did not answer on {}", health));
+                        }
+                    }
+                }
+                print_ready(&self.api_url, Some(url), api_key);
+                announced = true;
+            }
+
+            let status = child.wait().await.map_err(|e| e.to_string())?;
+            restarts += 1;
+            if restarts > MAX_FRONTEND_RESTARTS {
+                return Err(format!("The frontend exited {} times, last with {}", restarts, status));
+            }
+            let delay = restart_delay(restarts);
+            eprintln!(
+                "{}",
+                format!("⚠️  The frontend exited ({}), restarting in {}s", status, delay.as_secs()).yellow()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// 1s, 2s, 4s... between frontend restarts, at most 30s
+fn restart_delay(restarts: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(restarts.saturating_sub(1)).min(30))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_delay_backs_off() {
+        assert_eq!(restart_delay(1), Duration::from_secs(1));
+        assert_eq!(restart_delay(3), Duration::from_secs(4));
+        assert_eq!(restart_delay(10), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_masked_key_hides_secret() {
+        assert_eq!(masked_key("ak_1a2b3c4d.s3cr3t"), "ak_1a2b3c4d.********");
+        assert_eq!(masked_key("s3cr3t"), "********");
+    }
+}
@@ -0,0 +1,497 @@
+//! Installation diagnostics for R Commerce
+//!
+//! `rcommerce doctor` checks the database schema, data integrity, TLS
+//! certificates, background jobs, webhook delivery and payment gateway
+//! configuration, printing an actionable fix for every problem found.
+//! With `--json` the report is printed as JSON for monitoring systems.
+
+use colored::Colorize;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use rcommerce_core::cache::{RedisConfig, RedisPool};
+use rcommerce_core::config::PaymentConfig;
+use rcommerce_core::jobs::{JobConfig, JobQuery, JobQueue, JobStatus};
+use rcommerce_core::{Config, Migrator};
+
+/// Certificates expiring within this many days are reported as warnings
+const CERT_EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// Undelivered webhooks older than this many minutes count as backlog
+const WEBHOOK_BACKLOG_MINUTES: i64 = 60;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Skipped,
+    Warning,
+    Error,
+}
+
+/// Result of a single diagnostic check
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl CheckResult {
+    fn ok(name: &str, message: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Ok, message: message.into(), fix: None }
+    }
+
+    fn skipped(name: &str, message: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Skipped, message: message.into(), fix: None }
+    }
+
+    fn warning(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Warning, message: message.into(), fix: Some(fix.into()) }
+    }
+
+    fn error(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Error, message: message.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Full diagnostics report
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub version: String,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub status: CheckStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    fn new(checks: Vec<CheckResult>) -> Self {
+        let status = checks.iter().map(|c| c.status).max().unwrap_or(CheckStatus::Ok);
+        Self {
+            version: rcommerce_core::VERSION.to_string(),
+            checked_at: chrono::Utc::now(),
+            status,
+            checks,
+        }
+    }
+
+    /// Whether any check failed with an error
+    pub fn has_errors(&self) -> bool {
+        self.status == CheckStatus::Error
+    }
+}
+
+/// Run all diagnostics and print the report
+pub async fn run_doctor(config: &Config, json: bool) -> Result<DoctorReport, String> {
+    let mut checks = Vec::new();
+
+    checks.push(check_jwt_secret(config));
+
+    match crate::create_pool(config).await {
+        Ok(pool) => {
+            checks.push(CheckResult::ok("database", format!(
+                "Connected to {}:{}/{}",
+                config.database.host, config.database.port, config.database.database
+            )));
+            checks.push(check_schema(&pool).await);
+            checks.push(check_orphaned_orders(&pool).await);
+            checks.push(check_orphaned_payments(&pool).await);
+            checks.push(check_webhook_backlog(&pool).await);
+        }
+        Err(e) => {
+            checks.push(CheckResult::error(
+                "database",
+                format!("Cannot connect to database: {}", e),
+                "Check the [database] section of your config and that PostgreSQL is running",
+            ));
+            for name in ["schema", "orphaned_orders", "orphaned_payments", "webhook_backlog"] {
+                checks.push(CheckResult::skipped(name, "Database unavailable"));
+            }
+        }
+    }
+
+    checks.extend(check_certificates(config).await);
+    checks.push(check_stuck_jobs(config).await);
+    checks.extend(check_payment_gateways(&config.payment, |key| std::env::var(key).ok()));
+
+    let report = DoctorReport::new(checks);
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        println!("{}", output);
+    } else {
+        print_report(&report);
+    }
+
+    Ok(report)
+}
+
+fn print_report(report: &DoctorReport) {
+    println!("{}", "R Commerce Doctor".bold().underline());
+    println!();
+
+    for check in &report.checks {
+        let label = match check.status {
+            CheckStatus::Ok => "✓ ok     ".green(),
+            CheckStatus::Skipped => "- skipped".dimmed(),
+            CheckStatus::Warning => "⚠ warning".yellow(),
+            CheckStatus::Error => "✗ error  ".red(),
+        };
+        println!("{} {:<22} {}", label, check.name.bold(), check.message);
+        if let Some(ref fix) = check.fix {
+            println!("{:>34} {}", "fix:".cyan(), fix);
+        }
+    }
+
+    println!();
+    let count = |status| report.checks.iter().filter(|c| c.status == status).count();
+    let summary = format!(
+        "{} ok, {} warnings, {} errors, {} skipped",
+        count(CheckStatus::Ok),
+        count(CheckStatus::Warning),
+        count(CheckStatus::Error),
+        count(CheckStatus::Skipped)
+    );
+    match report.status {
+        CheckStatus::Error => println!("{}", format!("❌ {}", summary).red().bold()),
+        CheckStatus::Warning => println!("{}", format!("⚠️  {}", summary).yellow().bold()),
+        _ => println!("{}", format!("✅ {}", summary).green().bold()),
+    }
+}
+
+fn check_jwt_secret(config: &Config) -> CheckResult {
+    let secret = &config.security.jwt.secret;
+    if secret.is_empty() {
+        CheckResult::error(
+            "jwt_secret",
+            "JWT secret is not configured",
+            "Set security.jwt.secret to a random string of at least 32 characters",
+        )
+    } else if secret.len() < 32 {
+        CheckResult::warning(
+            "jwt_secret",
+            format!("JWT secret is only {} characters long", secret.len()),
+            "Use a JWT secret of at least 32 characters",
+        )
+    } else {
+        CheckResult::ok("jwt_secret", "JWT secret configured")
+    }
+}
+
+async fn check_schema(pool: &PgPool) -> CheckResult {
+    let migrator = Migrator::new(pool.clone());
+    match migrator.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => CheckResult::ok(
+            "schema",
+            format!("Schema is at version {}", Migrator::latest_version()),
+        ),
+        Ok(pending) => {
+            let names: Vec<String> = pending.iter().map(|(v, n)| format!("{:03}_{}", v, n)).collect();
+            CheckResult::error(
+                "schema",
+                format!("{} pending migration(s): {}", pending.len(), names.join(", ")),
+                "Run 'rcommerce db migrate'",
+            )
+        }
+        Err(e) => CheckResult::error(
+            "schema",
+            format!("Failed to read migration state: {}", e),
+            "Ensure the database user can read and create the _migrations table",
+        ),
+    }
+}
+
+async fn count(pool: &PgPool, sql: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(sql).fetch_one(pool).await
+}
+
+pub(crate) async fn table_exists(pool: &PgPool, table: &str) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT to_regclass($1) IS NOT NULL")
+        .bind(format!("public.{}", table))
+        .fetch_one(pool)
+        .await
+        .unwrap_or(false)
+}
+
+async fn check_orphaned_orders(pool: &PgPool) -> CheckResult {
+    // customer_id is nulled when a customer is deleted, so an order without a
+    // customer is only suspicious if no customer account matches its email either.
+    let sql = r#"
+        SELECT COUNT(*) FROM orders o
+        WHERE (o.customer_id IS NOT NULL
+               AND NOT EXISTS (SELECT 1 FROM customers c WHERE c.id = o.customer_id))
+           OR (o.customer_id IS NULL AND o.draft = false
+               AND EXISTS (SELECT 1 FROM customers c WHERE c.email = o.email))
+    "#;
+    match count(pool, sql).await {
+        Ok(0) => CheckResult::ok("orphaned_orders", "All orders reference valid customers"),
+        Ok(n) => CheckResult::warning(
+            "orphaned_orders",
+            format!("{} order(s) are not linked to an existing customer", n),
+            "Re-link orders by email: UPDATE orders o SET customer_id = c.id FROM customers c \
+             WHERE o.email = c.email AND (o.customer_id IS NULL OR o.customer_id <> c.id)",
+        ),
+        Err(e) => CheckResult::error("orphaned_orders", format!("Query failed: {}", e), "Run 'rcommerce db migrate'"),
+    }
+}
+
+async fn check_orphaned_payments(pool: &PgPool) -> CheckResult {
+    let sql = r#"
+        SELECT COUNT(*) FROM payments p
+        WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = p.order_id)
+    "#;
+    match count(pool, sql).await {
+        Ok(0) => CheckResult::ok("orphaned_payments", "All payments reference valid orders"),
+        Ok(n) => CheckResult::error(
+            "orphaned_payments",
+            format!("{} payment(s) reference missing orders", n),
+            "Investigate with: SELECT * FROM payments p WHERE NOT EXISTS \
+             (SELECT 1 FROM orders o WHERE o.id = p.order_id)",
+        ),
+        Err(e) => CheckResult::error("orphaned_payments", format!("Query failed: {}", e), "Run 'rcommerce db migrate'"),
+    }
+}
+
+async fn check_webhook_backlog(pool: &PgPool) -> CheckResult {
+    if !table_exists(pool, "webhook_deliveries").await {
+        return CheckResult::skipped("webhook_backlog", "Webhooks are not installed");
+    }
+
+    let sql = format!(
+        "SELECT COUNT(*) FROM webhook_deliveries \
+         WHERE delivered_at IS NULL AND created_at < NOW() - INTERVAL '{} minutes'",
+        WEBHOOK_BACKLOG_MINUTES
+    );
+    match count(pool, &sql).await {
+        Ok(0) => CheckResult::ok("webhook_backlog", "No undelivered webhooks"),
+        Ok(n) => CheckResult::warning(
+            "webhook_backlog",
+            format!("{} webhook deliveries pending for over {} minutes", n, WEBHOOK_BACKLOG_MINUTES),
+            "Check that webhook endpoints are reachable and inspect error_message in webhook_deliveries",
+        ),
+        Err(e) => CheckResult::error("webhook_backlog", format!("Query failed: {}", e), "Run 'rcommerce db migrate'"),
+    }
+}
+
+async fn check_certificates(config: &Config) -> Vec<CheckResult> {
+    let tls = &config.tls;
+    if !tls.enabled {
+        return vec![CheckResult::skipped("certificates", "TLS is disabled")];
+    }
+
+    let mut results = Vec::new();
+
+    if let Some(ref cert_file) = tls.cert_file {
+        if cert_file.exists() {
+            results.push(CheckResult::ok("certificates", format!("Certificate found at {}", cert_file.display())));
+        } else {
+            results.push(CheckResult::error(
+                "certificates",
+                format!("Certificate file {} does not exist", cert_file.display()),
+                "Fix tls.cert_file or enable tls.lets_encrypt",
+            ));
+        }
+        if let Some(ref key_file) = tls.key_file {
+            if !key_file.exists() {
+                results.push(CheckResult::error(
+                    "certificates",
+                    format!("Private key file {} does not exist", key_file.display()),
+                    "Fix tls.key_file",
+                ));
+            }
+        }
+    }
+
+    if let Some(ref le) = tls.lets_encrypt {
+        match crate::list_certificates(&le.cache_dir).await {
+            Ok(certs) if certs.is_empty() => results.push(CheckResult::warning(
+                "certificates",
+                format!("No Let's Encrypt certificates in {}", le.cache_dir.display()),
+                "Run 'rcommerce tls renew --domain <domain>'",
+            )),
+            Ok(certs) => {
+                let now = chrono::Utc::now();
+                for cert in certs {
+                    let days_left = (cert.expires_at - now).num_days();
+                    let name = format!("certificate:{}", cert.domain);
+                    let renew = format!("Run 'rcommerce tls renew --domain {}'", cert.domain);
+                    results.push(if days_left < 0 {
+                        CheckResult::error(&name, format!("Expired {} days ago", -days_left), renew)
+                    } else if days_left < CERT_EXPIRY_WARNING_DAYS {
+                        CheckResult::warning(&name, format!("Expires in {} days", days_left), renew)
+                    } else {
+                        CheckResult::ok(&name, format!("Valid for {} days", days_left))
+                    });
+                }
+            }
+            Err(e) => results.push(CheckResult::error(
+                "certificates",
+                format!("Failed to read certificate cache: {}", e),
+                format!("Check permissions on {}", le.cache_dir.display()),
+            )),
+        }
+    }
+
+    if results.is_empty() {
+        results.push(CheckResult::error(
+            "certificates",
+            "TLS is enabled but no certificate source is configured",
+            "Set tls.cert_file/tls.key_file or configure tls.lets_encrypt",
+        ));
+    }
+
+    results
+}
+
+async fn check_stuck_jobs(config: &Config) -> CheckResult {
+    let Some(url) = config.cache.redis_url.clone().or_else(|| std::env::var("REDIS_URL").ok()) else {
+        return CheckResult::skipped("stuck_jobs", "Redis is not configured");
+    };
+
+    let pool = match RedisPool::new(RedisConfig { url, ..Default::default() }).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            return CheckResult::error(
+                "stuck_jobs",
+                format!("Cannot connect to Redis: {}", e),
+                "Check cache.redis_url and that Redis is running",
+            )
+        }
+    };
+
+    let mut stuck = Vec::new();
+    for (queue_name, _) in JobConfig::default().queue.queues {
+        let queue = JobQueue::new(pool.clone(), queue_name.clone());
+        match queue.list_jobs(&JobQuery::new().with_status(JobStatus::Running)).await {
+            Ok(jobs) => stuck.extend(
+                jobs.into_iter()
+                    .filter(|job| job.has_timed_out())
+                    .map(|job| format!("{}:{} ({})", queue_name, job.id, job.job_type)),
+            ),
+            Err(e) => {
+                return CheckResult::error(
+                    "stuck_jobs",
+                    format!("Failed to list jobs in queue '{}': {}", queue_name, e),
+                    "Check Redis connectivity",
+                )
+            }
+        }
+    }
+
+    if stuck.is_empty() {
+        CheckResult::ok("stuck_jobs", "No running jobs past their timeout")
+    } else {
+        CheckResult::warning(
+            "stuck_jobs",
+            format!("{} job(s) running past their timeout: {}", stuck.len(), stuck.join(", ")),
+            "Restart the worker processes; timed-out jobs will be retried",
+        )
+    }
+}
+
+/// Check that every enabled gateway has the credentials the server needs to
+/// register it, from config or the same environment variables the server reads.
+fn check_payment_gateways(payment: &PaymentConfig, env: impl Fn(&str) -> Option<String>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    let mut registered = vec!["mock"];
+
+    let mut check = |gateway: &'static str, enabled: bool, required: &[(&str, &Option<String>, &str)]| {
+        let name = format!("gateway:{}", gateway);
+        if !enabled {
+            return;
+        }
+        let missing: Vec<String> = required
+            .iter()
+            .filter(|(_, value, env_var)| value.is_none() && env(env_var).is_none())
+            .map(|(key, _, env_var)| format!("payment.{}.{} (or {})", gateway, key, env_var))
+            .collect();
+        if missing.is_empty() {
+            registered.push(gateway);
+            results.push(CheckResult::ok(&name, "Credentials configured"));
+        } else {
+            results.push(CheckResult::error(
+                &name,
+                "Gateway is enabled but will not be registered: credentials missing",
+                format!("Set {}", missing.join(", ")),
+            ));
+        }
+    };
+
+    check("stripe", payment.stripe.enabled, &[
+        ("secret_key", &payment.stripe.secret_key, "STRIPE_API_KEY"),
+    ]);
+    check("wechatpay", payment.wechatpay.enabled, &[
+        ("mch_id", &payment.wechatpay.mch_id, "WECHATPAY_MCH_ID"),
+        ("app_id", &payment.wechatpay.app_id, "WECHATPAY_APP_ID"),
+        ("api_key", &payment.wechatpay.api_key, "WECHATPAY_API_KEY"),
+        ("serial_no", &payment.wechatpay.serial_no, "WECHATPAY_SERIAL_NO"),
+        ("private_key", &payment.wechatpay.private_key, "WECHATPAY_PRIVATE_KEY"),
+    ]);
+    check("alipay", payment.alipay.enabled, &[
+        ("app_id", &payment.alipay.app_id, "ALIPAY_APP_ID"),
+        ("private_key", &payment.alipay.private_key, "ALIPAY_PRIVATE_KEY"),
+        ("alipay_public_key", &payment.alipay.alipay_public_key, "ALIPAY_PUBLIC_KEY"),
+    ]);
+    check("airwallex", payment.airwallex.enabled, &[
+        ("client_id", &payment.airwallex.client_id, "AIRWALLEX_CLIENT_ID"),
+        ("api_key", &payment.airwallex.api_key, "AIRWALLEX_API_KEY"),
+    ]);
+
+    if !registered.contains(&payment.default_gateway.as_str()) {
+        results.push(CheckResult::error(
+            "gateway:default",
+            format!("Default gateway '{}' is not enabled and configured", payment.default_gateway),
+            format!("Enable payment.{} or change payment.default_gateway", payment.default_gateway),
+        ));
+    } else if payment.default_gateway == "mock" && !payment.test_mode {
+        results.push(CheckResult::warning(
+            "gateway:default",
+            "Default gateway is the mock gateway outside test mode",
+            "Set payment.default_gateway to a real gateway for production",
+        ));
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled_gateway_without_credentials_is_error() {
+        let mut payment = PaymentConfig::default();
+        payment.stripe.enabled = true;
+        payment.default_gateway = "stripe".to_string();
+
+        let results = check_payment_gateways(&payment, |_| None);
+        let stripe = results.iter().find(|r| r.name == "gateway:stripe").unwrap();
+        assert_eq!(stripe.status, CheckStatus::Error);
+        assert!(stripe.fix.as_ref().unwrap().contains("STRIPE_API_KEY"));
+        assert!(results.iter().any(|r| r.name == "gateway:default" && r.status == CheckStatus::Error));
+    }
+
+    #[test]
+    fn test_gateway_credentials_from_env() {
+        let mut payment = PaymentConfig::default();
+        payment.stripe.enabled = true;
+        payment.default_gateway = "stripe".to_string();
+
+        let results = check_payment_gateways(&payment, |key| Some(format!("{}-value", key)));
+        assert!(results.iter().all(|r| r.status == CheckStatus::Ok));
+    }
+
+    #[test]
+    fn test_report_status_is_worst_check() {
+        let report = DoctorReport::new(vec![
+            CheckResult::ok("a", "fine"),
+            CheckResult::warning("b", "meh", "fix it"),
+        ]);
+        assert_eq!(report.status, CheckStatus::Warning);
+        assert!(!report.has_errors());
+    }
+}
@@ -0,0 +1,71 @@
+//! Email template overrides
+//!
+//! `validate` builds the theme the server would send with (the branding,
+//! `notifications.email.templates_dir` and the stored templates) and checks
+//! every override for syntax errors, unknown variables and missing required
+//! ones. Previews from `email test` use the branding and template directory.
+
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use rcommerce_core::notification::theme::{self, EmailTheme};
+use rcommerce_core::repository::PostgresNotificationTemplateRepository;
+use rcommerce_core::Config;
+
+/// Install the branding and template directory for local previews
+pub fn install_local(config: &Config) -> Result<(), String> {
+    let email = &config.notifications.email;
+    let mut email_theme = EmailTheme::new(email.branding.clone());
+    if let Some(ref dir) = email.templates_dir {
+        email_theme = email_theme.with_directory(dir).map_err(|e| e.to_string())?;
+    }
+    theme::install(email_theme);
+    Ok(())
+}
+
+/// Check the overrides; returns the number of issues found
+pub async fn validate(config: &Config, dir: Option<PathBuf>, skip_db: bool, json: bool) -> Result<usize, String> {
+    let mut email = config.notifications.email.clone();
+    if dir.is_some() {
+        email.templates_dir = dir;
+    }
+    let email_theme = if skip_db {
+        theme::load(&email, None).await
+    } else {
+        let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+        theme::load(&email, Some(&PostgresNotificationTemplateRepository::new(pool))).await
+    }
+    .map_err(|e| e.to_string())?;
+    let issues = email_theme.validate();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues).map_err(|e| e.to_string())?);
+        return Ok(issues.len());
+    }
+
+    let overrides = email_theme.overrides();
+    if let Some(ref dir) = email.templates_dir {
+        println!("  Template directory: {}", dir.display().to_string().cyan());
+    }
+    if !skip_db {
+        println!("  Stored templates: {}", "included".cyan());
+    }
+    println!("  Overridden templates: {}", overrides.len());
+    println!();
+    for issue in &issues {
+        println!(
+            "{} {} ({}): {}",
+            "❌".red(),
+            issue.template.bold(),
+            issue.source.as_str().dimmed(),
+            issue.message
+        );
+    }
+    if issues.is_empty() {
+        println!("{}", "✅ All email template overrides are valid".green().bold());
+    } else {
+        eprintln!("\n{}", format!("❌ {} issues found", issues.len()).red().bold());
+    }
+    Ok(issues.len())
+}
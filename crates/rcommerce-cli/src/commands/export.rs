@@ -0,0 +1,50 @@
+//! Export products, customers or orders
+//!
+//! Writes CSV or JSON to a file, or to stdout when no `--output` is given
+//! so it can be piped. Rows are streamed from the database, so exports of
+//! any size run in constant memory. The same export is available from
+//! `GET /api/v1/export/:entity`.
+
+use colored::Colorize;
+use std::path::Path;
+use tokio::io::BufWriter;
+
+use rcommerce_core::models::{ExportEntity, ExportFilter, ExportFormat};
+use rcommerce_core::repository::PostgresExportRepository;
+use rcommerce_core::services::ExportService;
+use rcommerce_core::Config;
+
+/// Run an export; returns the number of rows written
+pub async fn run_export(
+    config: &Config,
+    entity: ExportEntity,
+    format: ExportFormat,
+    filter: ExportFilter,
+    output: Option<&Path>,
+) -> Result<u64, String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    let exports = ExportService::new(PostgresExportRepository::new(pool));
+    exports.validate(entity, &filter).map_err(|e| e.to_string())?;
+
+    let rows = match output {
+        Some(path) => {
+            let file = tokio::fs::File::create(path)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            let rows = exports
+                .write_to(entity, format, &filter, &mut BufWriter::new(file))
+                .await
+                .map_err(|e| e.to_string())?;
+            eprintln!(
+                "{}",
+                format!("✅ Exported {} {} to {}", rows, entity, path.display()).green().bold()
+            );
+            rows
+        }
+        None => exports
+            .write_to(entity, format, &filter, &mut BufWriter::new(tokio::io::stdout()))
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+    Ok(rows)
+}
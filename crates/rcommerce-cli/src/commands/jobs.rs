@@ -0,0 +1,239 @@
+//! Recurring job management
+//!
+//! Jobs are registered by API servers with the scheduler enabled (see
+//! `[scheduler]`); these commands change the schedules they share through
+//! the `scheduled_jobs` table. `run` asks for a one-off run, which the
+//! next server to poll picks up. Jobs that failed every retry of their
+//! `[scheduler.retry]` policy are listed by `failed` and can be retried or
+//! discarded by ID.
+
+use chrono::Utc;
+use colored::Colorize;
+use uuid::Uuid;
+
+use rcommerce_core::jobs::dead_letter;
+use rcommerce_core::jobs::recurring::next_run;
+use rcommerce_core::models::{ScheduledJob, DEAD_LETTER_DEAD, JOB_FAILED};
+use rcommerce_core::repository::{PostgresScheduledJobRepository, ScheduledJobRepository};
+use rcommerce_core::Config;
+
+async fn repository(config: &Config) -> Result<PostgresScheduledJobRepository, String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    Ok(PostgresScheduledJobRepository::new(pool))
+}
+
+fn not_found(name: &str) -> String {
+    format!("No job named '{}'; `rcommerce jobs list` shows the registered jobs", name)
+}
+
+fn state(job: &ScheduledJob) -> String {
+    if job.is_running(Utc::now()) {
+        format!("running on {}", job.locked_by.as_deref().unwrap_or("?")).cyan().to_string()
+    } else if job.paused {
+        "paused".yellow().to_string()
+    } else {
+        "active".green().to_string()
+    }
+}
+
+/// List jobs with their schedules and last runs
+pub async fn list(config: &Config, json: bool) -> Result<(), String> {
+    let jobs = repository(config).await?.list().await.map_err(|e| e.to_string())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&jobs).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    if jobs.is_empty() {
+        println!("No jobs registered yet; they appear once an API server with the scheduler enabled starts.");
+        return Ok(());
+    }
+
+    for job in &jobs {
+        println!("{}  {}", job.name.bold(), state(job));
+        if !job.description.is_empty() {
+            println!("  {}", job.description);
+        }
+        println!("  Schedule:  {}", job.schedule);
+        if !job.paused {
+            println!("  Next run:  {}", job.next_run_at.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+        if job.run_requested {
+            println!("  Run requested");
+        }
+        if let (Some(finished), Some(status)) = (job.last_finished_at, job.last_status.as_deref()) {
+            let status = if status == JOB_FAILED { status.red() } else { status.green() };
+            println!(
+                "  Last run:  {} {} in {}ms",
+                finished.format("%Y-%m-%d %H:%M:%S UTC"),
+                status,
+                job.last_duration_ms.unwrap_or_default()
+            );
+            if let Some(ref message) = job.last_message {
+                println!("             {}", message);
+            }
+        }
+        if job.failed_attempts > 0 {
+            println!("  Retrying:  {} failed attempts", job.failed_attempts);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Ask for a one-off run, even if the job is paused
+pub async fn run(config: &Config, name: &str) -> Result<(), String> {
+    let job = repository(config)
+        .await?
+        .request_run(name)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(name))?;
+
+    println!(
+        "{}",
+        format!("✅ Run of {} requested; a server picks it up within scheduler.poll_interval_secs", job.name)
+            .green()
+            .bold()
+    );
+    if job.is_running(Utc::now()) {
+        println!("It is running now on {}; the requested run follows.", job.locked_by.as_deref().unwrap_or("?"));
+    }
+    Ok(())
+}
+
+/// Stop scheduled runs of a job
+pub async fn pause(config: &Config, name: &str) -> Result<(), String> {
+    let job = repository(config)
+        .await?
+        .set_paused(name, true)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(name))?;
+
+    println!("{}", format!("⏸  Paused {}", job.name).yellow().bold());
+    if job.is_running(Utc::now()) {
+        println!("A run in progress on {} will finish.", job.locked_by.as_deref().unwrap_or("?"));
+    }
+    Ok(())
+}
+
+/// Resume scheduled runs from the next time the schedule fires
+pub async fn resume(config: &Config, name: &str) -> Result<(), String> {
+    let repository = repository(config).await?;
+    let job = repository
+        .set_paused(name, false)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(name))?;
+
+    // Don't catch up on runs missed while paused
+    let job = if job.next_run_at < Utc::now() {
+        let next_run_at = next_run(&job.schedule, Utc::now()).map_err(|e| e.to_string())?;
+        repository
+            .set_schedule(name, &job.schedule, next_run_at)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| not_found(name))?
+    } else {
+        job
+    };
+
+    println!(
+        "{}",
+        format!("▶  Resumed {}; next run {}", job.name, job.next_run_at.format("%Y-%m-%d %H:%M:%S UTC"))
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Change a job's cron schedule
+pub async fn schedule(config: &Config, name: &str, expression: &str) -> Result<(), String> {
+    let next_run_at = next_run(expression, Utc::now()).map_err(|e| e.to_string())?;
+    let job = repository(config)
+        .await?
+        .set_schedule(name, expression.trim(), next_run_at)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| not_found(name))?;
+
+    println!(
+        "{}",
+        format!(
+            "✅ {} now runs on '{}'; next run {}",
+            job.name,
+            job.schedule,
+            job.next_run_at.format("%Y-%m-%d %H:%M:%S UTC")
+        )
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+/// List dead-lettered jobs, newest first
+pub async fn failed(config: &Config, all: bool, json: bool) -> Result<(), String> {
+    let status = if all { None } else { Some(DEAD_LETTER_DEAD) };
+    let entries = repository(config)
+        .await?
+        .dead_letters(status, 100, 0)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("No failed jobs.");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        let status = if entry.status == DEAD_LETTER_DEAD { entry.status.red() } else { entry.status.normal() };
+        println!("{}  {}  {}", entry.id.to_string().bold(), entry.job_name, status);
+        println!(
+            "  Failed {} attempts, last at {}",
+            entry.attempts,
+            entry.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        for failure in entry.failures.iter() {
+            println!(
+                "  #{} {} on {} after {}ms: {}",
+                failure.attempt,
+                failure.failed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                failure.instance,
+                failure.duration_ms,
+                failure.error
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Run a dead-lettered job again on the next scheduler poll
+pub async fn retry(config: &Config, id: Uuid) -> Result<(), String> {
+    let entry = dead_letter::retry(&repository(config).await?, id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "{}",
+        format!("✅ Run of {} requested; a server picks it up within scheduler.poll_interval_secs", entry.job_name)
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Discard a dead-lettered job without running it again
+pub async fn discard(config: &Config, id: Uuid) -> Result<(), String> {
+    let entry = dead_letter::discard(&repository(config).await?, id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!("{}", format!("🗑  Discarded failed run of {} ({})", entry.job_name, entry.id).yellow().bold());
+    Ok(())
+}
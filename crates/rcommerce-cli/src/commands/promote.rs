@@ -0,0 +1,204 @@
+//! Promote catalog changes between instances
+//!
+//! Fetches the catalog snapshot (`GET /api/v1/admin/catalog/snapshot`) of
+//! a source instance (usually staging) and a target (usually production),
+//! shows the changes that would make the target match, and applies the
+//! approved ones with `POST /api/v1/admin/catalog/changes`. Records missing
+//! from the source are only removed from the target with `--delete`.
+
+use colored::Colorize;
+use dialoguer::MultiSelect;
+use std::time::Duration;
+
+use rcommerce_core::models::{
+    ApplyCatalogChangesRequest, CatalogApplyResult, CatalogChange, CatalogChangeAction, CatalogDiffOptions,
+    CatalogEntity, CatalogSnapshot,
+};
+use rcommerce_core::services::diff_catalogs;
+
+const SNAPSHOT_PATH: &str = "/api/v1/admin/catalog/snapshot";
+const CHANGES_PATH: &str = "/api/v1/admin/catalog/changes";
+
+/// Options for a promotion run
+#[derive(Debug, Clone)]
+pub struct PromoteOptions {
+    pub source: String,
+    pub source_token: String,
+    pub target: String,
+    pub target_token: String,
+    pub entities: Vec<CatalogEntity>,
+    pub include_deletes: bool,
+    pub dry_run: bool,
+    pub yes: bool,
+}
+
+/// One line per change, e.g. `~ product runner (price, title)`
+pub(crate) fn describe(change: &CatalogChange) -> String {
+    match change.action {
+        CatalogChangeAction::Create => format!("+ {} {}", change.entity, change.key),
+        CatalogChangeAction::Update => {
+            format!("~ {} {} ({})", change.entity, change.key, change.fields.join(", "))
+        }
+        CatalogChangeAction::Delete if change.entity == CatalogEntity::Product => {
+            format!("- {} {} (deactivate)", change.entity, change.key)
+        }
+        CatalogChangeAction::Delete => format!("- {} {}", change.entity, change.key),
+    }
+}
+
+fn url(base: &str, path: &str) -> String {
+    format!("{}{}", base.trim_end_matches('/'), path)
+}
+
+async fn fetch_snapshot(client: &reqwest::Client, base: &str, token: &str) -> Result<CatalogSnapshot, String> {
+    let response = client
+        .get(url(base, SNAPSHOT_PATH))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch catalog from {}: {}", base, e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("{} returned {} for its catalog: {}", base, status, body));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid catalog from {}: {}", base, e))
+}
+
+/// Promote the source's catalog changes to the target; returns the number
+/// of changes applied
+pub async fn run_promote(options: PromoteOptions) -> Result<usize, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let (source, target) = tokio::try_join!(
+        fetch_snapshot(&client, &options.source, &options.source_token),
+        fetch_snapshot(&client, &options.target, &options.target_token),
+    )?;
+
+    let diff_options = CatalogDiffOptions {
+        entities: options.entities.clone(),
+        include_deletes: options.include_deletes,
+    };
+    let changes = diff_catalogs(&source, &target, &diff_options);
+    if changes.is_empty() {
+        println!("{}", format!("✓ {} already matches {}", options.target, options.source).green());
+        return Ok(0);
+    }
+
+    println!(
+        "{}",
+        format!("{} changes to promote from {} to {}:", changes.len(), options.source, options.target).bold()
+    );
+    for change in &changes {
+        let line = describe(change);
+        match change.action {
+            CatalogChangeAction::Create => println!("  {}", line.green()),
+            CatalogChangeAction::Update => println!("  {}", line.yellow()),
+            CatalogChangeAction::Delete => println!("  {}", line.red()),
+        }
+    }
+
+    if options.dry_run {
+        return Ok(0);
+    }
+
+    let approved: Vec<CatalogChange> = if options.yes {
+        changes
+    } else {
+        let items: Vec<String> = changes.iter().map(describe).collect();
+        let selected = MultiSelect::new()
+            .with_prompt("Changes to apply (space to toggle, enter to confirm)")
+            .items(&items)
+            .defaults(&vec![true; items.len()])
+            .interact()
+            .map_err(|e| format!("Failed to read approval: {}", e))?;
+        changes
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| selected.contains(i))
+            .map(|(_, change)| change)
+            .collect()
+    };
+    if approved.is_empty() {
+        println!("No changes approved");
+        return Ok(0);
+    }
+
+    let count = approved.len();
+    let response = client
+        .post(url(&options.target, CHANGES_PATH))
+        .bearer_auth(&options.target_token)
+        .json(&ApplyCatalogChangesRequest { changes: approved })
+        .send()
+        .await
+        .map_err(|e| format!("Failed to apply changes to {}: {}", options.target, e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("{} rejected the changes ({}): {}", options.target, status, body));
+    }
+    let result: CatalogApplyResult = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from {}: {}", options.target, e))?;
+
+    println!(
+        "{}",
+        format!(
+            "✓ Promoted {} changes: {} created, {} updated, {} deleted",
+            count, result.created, result.updated, result.deleted
+        )
+        .green()
+        .bold()
+    );
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(entity: CatalogEntity, action: CatalogChangeAction, fields: &[&str]) -> CatalogChange {
+        CatalogChange {
+            entity,
+            key: "runner".to_string(),
+            action,
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            record: None,
+        }
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(&change(CatalogEntity::Category, CatalogChangeAction::Create, &[])),
+            "+ category runner"
+        );
+        assert_eq!(
+            describe(&change(CatalogEntity::Product, CatalogChangeAction::Update, &["price", "title"])),
+            "~ product runner (price, title)"
+        );
+        assert_eq!(
+            describe(&change(CatalogEntity::Product, CatalogChangeAction::Delete, &[])),
+            "- product runner (deactivate)"
+        );
+        assert_eq!(
+            describe(&change(CatalogEntity::ShippingRule, CatalogChangeAction::Delete, &[])),
+            "- shipping_rule runner"
+        );
+    }
+
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            url("https://staging.example.com/", SNAPSHOT_PATH),
+            "https://staging.example.com/api/v1/admin/catalog/snapshot"
+        );
+    }
+}
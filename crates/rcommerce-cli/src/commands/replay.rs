@@ -0,0 +1,170 @@
+//! Replay captured traffic against another server
+//!
+//! Reads a capture downloaded from `GET /api/v1/admin/capture` and re-sends
+//! each request to a target (usually staging), comparing the status codes
+//! with what production returned. Credentials are redacted in captures, so
+//! pass `--token` to authenticate against the target. Only GET and HEAD
+//! requests are replayed unless `--include-writes` is given.
+
+use colored::Colorize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rcommerce_api::middleware::capture::{CapturedExchange, REDACTED};
+
+/// Headers that must not be copied from the capture to the replayed request
+const SKIPPED_HEADERS: &[&str] = &["host", "content-length", "connection", "transfer-encoding"];
+
+/// Options for a replay run
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub target: String,
+    pub token: Option<String>,
+    pub path_prefix: Option<String>,
+    pub include_writes: bool,
+    pub delay_ms: u64,
+    pub dry_run: bool,
+}
+
+/// Select the exchanges to replay
+fn select<'a>(exchanges: &'a [CapturedExchange], options: &ReplayOptions) -> Vec<&'a CapturedExchange> {
+    exchanges
+        .iter()
+        .filter(|e| options.include_writes || matches!(e.method.as_str(), "GET" | "HEAD"))
+        .filter(|e| {
+            options
+                .path_prefix
+                .as_ref()
+                .map(|prefix| e.uri.starts_with(prefix.as_str()))
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+/// Replay a capture file; returns the number of status mismatches
+pub async fn run_replay(file: &Path, options: ReplayOptions) -> Result<usize, String> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let exchanges: Vec<CapturedExchange> = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid capture file: {}", e))?;
+
+    let selected = select(&exchanges, &options);
+    println!(
+        "{}",
+        format!(
+            "Replaying {} of {} captured requests against {}",
+            selected.len(),
+            exchanges.len(),
+            options.target
+        )
+        .bold()
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let base = options.target.trim_end_matches('/');
+
+    let mut mismatches = 0;
+    let mut failures = 0;
+
+    for exchange in selected {
+        let method = reqwest::Method::from_bytes(exchange.method.as_bytes())
+            .map_err(|e| format!("Invalid method '{}': {}", exchange.method, e))?;
+
+        if options.dry_run {
+            println!("  {:<6} {} (expected {})", exchange.method, exchange.uri, exchange.status);
+            continue;
+        }
+
+        let mut request = client.request(method, format!("{}{}", base, exchange.uri));
+        for (name, value) in &exchange.request_headers {
+            if value != REDACTED && !SKIPPED_HEADERS.contains(&name.as_str()) {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        if let Some(ref token) = options.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(ref body) = exchange.request_body {
+            request = request.json(body);
+        }
+
+        let start = Instant::now();
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let elapsed = start.elapsed().as_millis();
+                let line = format!(
+                    "  {:<6} {} {} -> {} ({}ms, was {}ms)",
+                    exchange.method, exchange.uri, exchange.status, status, elapsed, exchange.duration_ms
+                );
+                if status == exchange.status {
+                    println!("{} {}", "✓".green(), line);
+                } else {
+                    mismatches += 1;
+                    println!("{} {}", "✗".red(), line.red());
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                println!("{} {:<6} {} {}", "✗".red(), exchange.method, exchange.uri, e.to_string().red());
+            }
+        }
+
+        if options.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(options.delay_ms)).await;
+        }
+    }
+
+    if !options.dry_run {
+        println!();
+        println!("{} status mismatches, {} request failures", mismatches, failures);
+    }
+
+    Ok(mismatches + failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn exchange(method: &str, uri: &str) -> CapturedExchange {
+        CapturedExchange {
+            id: uuid::Uuid::new_v4(),
+            captured_at: chrono::Utc::now(),
+            duration_ms: 1,
+            method: method.to_string(),
+            uri: uri.to_string(),
+            request_headers: BTreeMap::new(),
+            request_body: None,
+            status: 200,
+            response_headers: BTreeMap::new(),
+            response_body: None,
+        }
+    }
+
+    #[test]
+    fn test_select_skips_writes_and_filters_prefix() {
+        let exchanges = vec![
+            exchange("GET", "/api/v1/products"),
+            exchange("POST", "/api/v1/orders"),
+            exchange("GET", "/api/v1/orders/1"),
+        ];
+        let mut options = ReplayOptions {
+            target: "http://localhost".to_string(),
+            token: None,
+            path_prefix: None,
+            include_writes: false,
+            delay_ms: 0,
+            dry_run: true,
+        };
+        assert_eq!(select(&exchanges, &options).len(), 2);
+
+        options.include_writes = true;
+        options.path_prefix = Some("/api/v1/orders".to_string());
+        assert_eq!(select(&exchanges, &options).len(), 2);
+    }
+}
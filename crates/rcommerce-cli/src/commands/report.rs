@@ -0,0 +1,205 @@
+//! Sales report summaries for the terminal
+//!
+//! Reads the same daily rollups as `/api/v1/admin/sales` (see
+//! `rcommerce_core::reports::sales`), which the `sales_views_refresh` job
+//! refreshes; `--refresh` (or `rcommerce report refresh`) refreshes them
+//! first for up-to-the-minute figures.
+
+use chrono::{Duration, NaiveDate, Utc};
+use colored::Colorize;
+
+use rcommerce_core::reports::{Granularity, SalesRange, SalesReportService, TopProductsBy};
+use rcommerce_core::repository::PostgresSalesReportRepository;
+use rcommerce_core::Config;
+
+/// Options of `rcommerce report summary`
+#[derive(Debug)]
+pub struct SummaryOptions {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub days: i64,
+    pub granularity: Granularity,
+    pub top: i64,
+    pub refresh: bool,
+    pub json: bool,
+}
+
+impl SummaryOptions {
+    /// `--from`/`--to` if given, else the last `--days` days; a range with
+    /// only one end spans `--days` days from or to it
+    fn range(&self, today: NaiveDate) -> Result<SalesRange, String> {
+        let range = match (self.from, self.to) {
+            (None, None) => SalesRange::last_days(self.days, today),
+            (Some(from), None) => SalesRange::new(from, (from + Duration::days(self.days - 1)).min(today)),
+            (None, Some(to)) => SalesRange::last_days(self.days, to),
+            (Some(from), Some(to)) => SalesRange::new(from, to),
+        };
+        range.map_err(|e| e.to_string())
+    }
+}
+
+async fn service(config: &Config) -> Result<SalesReportService<PostgresSalesReportRepository>, String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    Ok(SalesReportService::new(PostgresSalesReportRepository::new(pool), config.reports.clone()))
+}
+
+fn rate(rate: Option<f64>) -> String {
+    rate.map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate))
+}
+
+/// Print revenue, refunds, average order value, channels and top products
+pub async fn summary(config: &Config, options: SummaryOptions) -> Result<(), String> {
+    let range = options.range(Utc::now().date_naive())?;
+    let sales = service(config).await?;
+    if options.refresh {
+        sales.refresh().await.map_err(|e| e.to_string())?;
+    }
+
+    let revenue = sales.revenue(range, options.granularity).await.map_err(|e| e.to_string())?;
+    let refunds = sales.refunds(range, options.granularity).await.map_err(|e| e.to_string())?;
+    let order_value = sales
+        .average_order_value(range, options.granularity)
+        .await
+        .map_err(|e| e.to_string())?;
+    let channels = sales.channels(range).await.map_err(|e| e.to_string())?;
+    let top = sales
+        .top_products(range, TopProductsBy::Revenue, options.top)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if options.json {
+        let report = serde_json::json!({
+            "revenue": revenue,
+            "refunds": refunds,
+            "average_order_value": order_value,
+            "channels": channels,
+            "top_products": top,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+
+    let refreshed = revenue
+        .refreshed_at
+        .map_or_else(|| "never".to_string(), |at| at.format("%Y-%m-%d %H:%M UTC").to_string());
+    println!("{}", format!("Sales {} to {}", range.from, range.to).bold().underline());
+    println!("Figures as of {} (base currency)\n", refreshed);
+
+    println!(
+        "{:<12} {:>8} {:>8} {:>8} {:>14} {:>12} {:>14}",
+        "Period", "Orders", "Paid", "Items", "Revenue", "Refunded", "Net"
+    );
+    println!("{}", "-".repeat(84));
+    for period in &revenue.periods {
+        let figures = &period.figures;
+        println!(
+            "{:<12} {:>8} {:>8} {:>8} {:>14.2} {:>12.2} {:>14.2}",
+            period.period.to_string(),
+            figures.orders,
+            figures.paid_orders,
+            figures.items_sold,
+            figures.gross_revenue,
+            figures.refunded_amount,
+            figures.net_revenue
+        );
+    }
+    let total = &revenue.total;
+    println!("{}", "-".repeat(84));
+    println!(
+        "{:<12} {:>8} {:>8} {:>8} {:>14.2} {:>12.2} {:>14.2}",
+        "Total",
+        total.orders,
+        total.paid_orders,
+        total.items_sold,
+        total.gross_revenue,
+        total.refunded_amount,
+        total.net_revenue
+    );
+
+    println!("\nAverage order value: {:.2}", order_value.total.average_order_value);
+    println!(
+        "Refund rate:         {} of paid orders, {} of revenue",
+        rate(refunds.total.refund_rate),
+        rate(refunds.total.refunded_revenue_rate)
+    );
+
+    if !channels.channels.is_empty() {
+        println!("\n{}", "Channels".bold());
+        println!(
+            "{:<12} {:>8} {:>8} {:>9} {:>14} {:>10} {:>10} {:>11}",
+            "Channel", "Orders", "Paid", "Paid %", "Revenue", "AOV", "Sessions", "Conversion"
+        );
+        for channel in &channels.channels {
+            println!(
+                "{:<12} {:>8} {:>8} {:>9} {:>14.2} {:>10.2} {:>10} {:>11}",
+                channel.channel,
+                channel.orders,
+                channel.paid_orders,
+                rate(channel.payment_rate),
+                channel.gross_revenue,
+                channel.average_order_value,
+                channel.sessions.map_or_else(|| "-".to_string(), |sessions| sessions.to_string()),
+                rate(channel.conversion_rate)
+            );
+        }
+    }
+
+    if !top.products.is_empty() {
+        println!("\n{}", "Top products".bold());
+        for (rank, product) in top.products.iter().enumerate() {
+            println!(
+                "{:>3}. {:<40} {:>8} units {:>14.2}",
+                rank + 1,
+                crate::truncate(&product.title, 40),
+                product.units,
+                product.revenue
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Refresh the sales rollups now
+pub async fn refresh(config: &Config) -> Result<(), String> {
+    let refreshed_at = service(config).await?.refresh().await.map_err(|e| e.to_string())?;
+    println!(
+        "{}",
+        format!("✅ Sales figures refreshed at {}", refreshed_at.format("%Y-%m-%d %H:%M:%S UTC"))
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn options(from: Option<&str>, to: Option<&str>) -> SummaryOptions {
+        SummaryOptions {
+            from: from.map(date),
+            to: to.map(date),
+            days: 7,
+            granularity: Granularity::Day,
+            top: 5,
+            refresh: false,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_summary_range() {
+        let today = date("2026-10-16");
+        let range = options(None, None).range(today).unwrap();
+        assert_eq!((range.from, range.to), (date("2026-10-10"), today));
+        let range = options(Some("2026-10-01"), None).range(today).unwrap();
+        assert_eq!((range.from, range.to), (date("2026-10-01"), date("2026-10-07")));
+        let range = options(None, Some("2026-09-30")).range(today).unwrap();
+        assert_eq!(range.from, date("2026-09-24"));
+        assert!(options(Some("2026-10-02"), Some("2026-10-01")).range(today).is_err());
+    }
+}
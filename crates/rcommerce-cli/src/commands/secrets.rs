@@ -0,0 +1,67 @@
+//! Master key tooling for secrets stored in the database
+//!
+//! `generate-key` prints a new master key. `rotate` seals plain-text
+//! secrets and rewraps ones sealed under a previous master key, after the
+//! new key has been made the master key and the old one listed as a
+//! previous key (see `[secrets]`). `status` shows how many are pending.
+
+use colored::Colorize;
+
+use rcommerce_core::repository::PostgresSecretRepository;
+use rcommerce_core::secrets::{MasterKey, SecretBox};
+use rcommerce_core::services::SecretService;
+use rcommerce_core::Config;
+
+async fn secret_service(config: &Config) -> Result<SecretService<PostgresSecretRepository>, String> {
+    let secrets = SecretBox::from_config(&config.secrets).map_err(|e| e.to_string())?;
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    Ok(SecretService::new(PostgresSecretRepository::new(pool), secrets))
+}
+
+/// Print a new base64 master key
+pub fn generate_key() -> Result<(), String> {
+    let key = MasterKey::generate().map_err(|e| e.to_string())?;
+    println!("{}", key);
+    eprintln!(
+        "Set it as {} (or write it to secrets.master_key_file) and keep the old key as a previous key until `rcommerce secrets rotate` has run.",
+        Config::default().secrets.master_key_env
+    );
+    Ok(())
+}
+
+/// Re-encrypt stored secrets under the current master key; returns the
+/// number that could not be opened
+pub async fn rotate(config: &Config, dry_run: bool) -> Result<usize, String> {
+    let service = secret_service(config).await?;
+    let key_id = service.secrets().current_key_id().unwrap_or_default().to_string();
+    let report = service.reencrypt(dry_run).await.map_err(|e| e.to_string())?;
+
+    let verb = if dry_run { "would be re-encrypted" } else { "re-encrypted" };
+    println!(
+        "{}",
+        format!("✅ {} of {} secrets {} with master key {}", report.resealed, report.checked, verb, key_id)
+            .green()
+            .bold()
+    );
+    if report.skipped > 0 {
+        println!("{} secrets changed during the run; run it again to cover them", report.skipped);
+    }
+    if report.failed > 0 {
+        eprintln!(
+            "{}",
+            format!("❌ {} secrets could not be opened; is their master key listed as a previous key?", report.failed).red()
+        );
+    }
+    Ok(report.failed)
+}
+
+/// Show the master key in use and how many secrets need re-encrypting
+pub async fn status(config: &Config) -> Result<(), String> {
+    let service = secret_service(config).await?;
+    match service.secrets().current_key_id() {
+        Some(key_id) => println!("Master key:  {}", key_id),
+        None => println!("Master key:  {}", "not configured (secrets are stored in plain text)".yellow()),
+    }
+    println!("Pending:     {}", service.pending().await.map_err(|e| e.to_string())?);
+    Ok(())
+}
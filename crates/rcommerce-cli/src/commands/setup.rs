@@ -821,6 +821,7 @@ async fn setup_import(config: &Config) -> Result<bool, String> {
             skip_existing: !overwrite,
             ..Default::default()
         },
+        media: Some(config.media.clone()),
     };
 
     // Get importer and run
@@ -307,20 +307,20 @@ impl Shell {
         println!();
 
         // Get counts from database
-        let product_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products")
+        let product_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let order_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders")
+        let order_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let customer_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM customers")
+        let customer_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM customers WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
         let total_revenue: Option<Decimal> = sqlx::query_scalar(
-            "SELECT SUM(total) FROM orders WHERE status NOT IN ('cancelled', 'refunded')"
+            "SELECT SUM(total) FROM orders WHERE status NOT IN ('cancelled', 'refunded') AND deleted_at IS NULL"
         )
             .fetch_one(&self.pool)
             .await?;
@@ -330,6 +330,7 @@ impl Shell {
             "SELECT o.id::text, c.email, o.total, o.status::text 
              FROM orders o 
              JOIN customers c ON o.customer_id = c.id 
+             WHERE o.deleted_at IS NULL
              ORDER BY o.created_at DESC 
              LIMIT 5"
         )
@@ -375,23 +376,23 @@ impl Shell {
         println!("\n{}", "Database Status".bold().underline());
         println!();
 
-        let product_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products")
+        let product_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let active_products: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products WHERE is_active = true")
+        let active_products: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products WHERE is_active = true AND deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let order_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders")
+        let order_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let pending_orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders WHERE status = 'pending'")
+        let pending_orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders WHERE status = 'pending' AND deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
-        let customer_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM customers")
+        let customer_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM customers WHERE deleted_at IS NULL")
             .fetch_one(&self.pool)
             .await?;
         
@@ -417,6 +418,7 @@ impl Shell {
         let products: Vec<ProductRecord> = sqlx::query_as(
             "SELECT id, title, slug, price, currency::text, description, is_active, inventory_quantity, created_at 
              FROM products 
+             WHERE deleted_at IS NULL
              ORDER BY created_at DESC 
              LIMIT $1"
         )
@@ -456,6 +458,7 @@ impl Shell {
             "SELECT o.id, c.email as customer_email, o.status::text, o.total, o.created_at 
              FROM orders o 
              JOIN customers c ON o.customer_id = c.id 
+             WHERE o.deleted_at IS NULL
              ORDER BY o.created_at DESC 
              LIMIT $1"
         )
@@ -499,6 +502,7 @@ impl Shell {
         let customers: Vec<CustomerRecord> = sqlx::query_as(
             "SELECT id, email, first_name, last_name, created_at 
              FROM customers 
+             WHERE deleted_at IS NULL
              ORDER BY created_at DESC 
              LIMIT $1"
         )
@@ -575,7 +579,7 @@ impl Shell {
         
         let product: Option<ProductRecord> = sqlx::query_as(
             "SELECT id, title, slug, price, currency::text, description, is_active, inventory_quantity, created_at 
-             FROM products WHERE id = $1"
+             FROM products WHERE id = $1 AND deleted_at IS NULL"
         )
             .bind(product_id)
             .fetch_optional(&self.pool)
@@ -613,7 +617,7 @@ impl Shell {
                     o.currency::text, o.notes, o.created_at, o.updated_at
              FROM orders o 
              JOIN customers c ON o.customer_id = c.id 
-             WHERE o.id = $1"
+             WHERE o.id = $1 AND o.deleted_at IS NULL"
         )
             .bind(order_id)
             .fetch_optional(&self.pool)
@@ -674,7 +678,7 @@ impl Shell {
         let customer: Option<CustomerDetailRecord> = sqlx::query_as(
             "SELECT id, email, first_name, last_name, phone, accepts_marketing, 
                     currency::text, is_verified, created_at, updated_at
-             FROM customers WHERE id = $1"
+             FROM customers WHERE id = $1 AND deleted_at IS NULL"
         )
             .bind(customer_id)
             .fetch_optional(&self.pool)
@@ -1077,12 +1081,12 @@ impl Shell {
         Ok(())
     }
 
-    /// Delete a product
+    /// Delete a product; restorable until the purge job removes it
     async fn delete_product(&self, id: &str) -> Result<()> {
         let product_id = parse_uuid(id)?;
         
         // Check if product exists
-        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM products WHERE id = $1)")
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM products WHERE id = $1 AND deleted_at IS NULL)")
             .bind(product_id)
             .fetch_one(&self.pool)
             .await?;
@@ -1104,7 +1108,7 @@ impl Shell {
             return Ok(());
         }
         
-        sqlx::query("DELETE FROM products WHERE id = $1")
+        sqlx::query("UPDATE products SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1")
             .bind(product_id)
             .execute(&self.pool)
             .await?;
@@ -1113,12 +1117,12 @@ impl Shell {
         Ok(())
     }
 
-    /// Delete a customer
+    /// Delete a customer; their orders are kept and they stay restorable until purged
     async fn delete_customer(&self, id: &str) -> Result<()> {
         let customer_id = parse_uuid(id)?;
         
         // Check if customer exists
-        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM customers WHERE id = $1)")
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM customers WHERE id = $1 AND deleted_at IS NULL)")
             .bind(customer_id)
             .fetch_one(&self.pool)
             .await?;
@@ -1128,17 +1132,6 @@ impl Shell {
             return Ok(());
         }
         
-        // Check if customer has orders
-        let has_orders: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM orders WHERE customer_id = $1)")
-            .bind(customer_id)
-            .fetch_one(&self.pool)
-            .await?;
-        
-        if has_orders {
-            println!("{} Cannot delete customer '{}' - they have existing orders", "Error:".red(), id);
-            return Ok(());
-        }
-        
         // Confirm deletion
         let confirmed = Confirm::new()
             .with_prompt(format!("Are you sure you want to delete customer '{}'?", id))
@@ -1151,7 +1144,7 @@ impl Shell {
             return Ok(());
         }
         
-        sqlx::query("DELETE FROM customers WHERE id = $1")
+        sqlx::query("UPDATE customers SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1")
             .bind(customer_id)
             .execute(&self.pool)
             .await?;
@@ -1201,7 +1194,7 @@ impl Shell {
         let products: Vec<ProductRecord> = sqlx::query_as(
             "SELECT id, title, slug, price, currency::text, description, is_active, inventory_quantity, created_at 
              FROM products 
-             WHERE title ILIKE $1 OR slug ILIKE $1 OR description ILIKE $1
+             WHERE (title ILIKE $1 OR slug ILIKE $1 OR description ILIKE $1) AND deleted_at IS NULL
              ORDER BY created_at DESC 
              LIMIT 20"
         )
@@ -1240,7 +1233,7 @@ impl Shell {
         let customers: Vec<CustomerRecord> = sqlx::query_as(
             "SELECT id, email, first_name, last_name, created_at 
              FROM customers 
-             WHERE email ILIKE $1 OR first_name ILIKE $1 OR last_name ILIKE $1
+             WHERE (email ILIKE $1 OR first_name ILIKE $1 OR last_name ILIKE $1) AND deleted_at IS NULL
              ORDER BY created_at DESC 
              LIMIT 20"
         )
@@ -1279,7 +1272,7 @@ impl Shell {
             "SELECT o.id, c.email as customer_email, o.status::text, o.total, o.created_at 
              FROM orders o 
              JOIN customers c ON o.customer_id = c.id 
-             WHERE c.email ILIKE $1 OR o.id::text ILIKE $1
+             WHERE (c.email ILIKE $1 OR o.id::text ILIKE $1) AND o.deleted_at IS NULL
              ORDER BY o.created_at DESC 
              LIMIT 20"
         )
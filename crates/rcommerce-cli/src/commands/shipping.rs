@@ -0,0 +1,79 @@
+//! Shipping commands for warehouse print stations
+//!
+//! `print-batch` merges the shipping labels or packing slips of shipments
+//! into one PDF laid out for a printer profile (`[printing]`) and writes
+//! it to a file. With `--print` it is sent to the profile's
+//! `print_command`, and the batch is recorded printed (or failed with the
+//! printer's error) like `POST /api/v1/admin/print-batches/:id/printed`.
+
+use colored::Colorize;
+use std::path::PathBuf;
+
+use rcommerce_core::models::CreatePrintBatchRequest;
+use rcommerce_core::repository::PostgresPrintBatchRepository;
+use rcommerce_core::services::PrintService;
+use rcommerce_core::Config;
+
+/// Generate a print batch, write its PDF and optionally print it
+pub async fn print_batch(
+    config: &Config,
+    request: CreatePrintBatchRequest,
+    output: Option<PathBuf>,
+    print: bool,
+) -> Result<(), String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    let printing = PrintService::new(PostgresPrintBatchRepository::new(pool), config.printing.clone());
+
+    let batch = printing.create_batch(request, None).await.map_err(|e| e.to_string())?;
+    let pdf = printing.pdf(batch.id).await.map_err(|e| e.to_string())?;
+    let path = output.unwrap_or_else(|| PathBuf::from(format!("print-batch-{}.pdf", batch.id)));
+    tokio::fs::write(&path, &pdf)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    println!(
+        "{}",
+        format!(
+            "✅ Batch {}: {} {} documents, {} pages on '{}' written to {}",
+            batch.id, batch.document_count, batch.document_kind, batch.page_count, batch.profile, path.display()
+        )
+        .green()
+        .bold()
+    );
+
+    if !print {
+        return Ok(());
+    }
+    let profile = printing.profile(&batch.profile).map_err(|e| e.to_string())?;
+    let Some(command) = profile.print_command.as_deref() else {
+        return Err(format!("Printer profile '{}' has no print_command", profile.name));
+    };
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("print_command is empty")?;
+    let output = tokio::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .output()
+        .await;
+
+    let error = match output {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => Some(format!("Failed to run {}: {}", program, e)),
+    };
+    match error {
+        None => {
+            printing.mark_printed(batch.id).await.map_err(|e| e.to_string())?;
+            println!("{}", format!("🖨  Sent to {}", command).green());
+            Ok(())
+        }
+        Some(error) => {
+            printing.mark_failed(batch.id, &error).await.map_err(|e| e.to_string())?;
+            Err(error)
+        }
+    }
+}
@@ -0,0 +1,552 @@
+//! Data integrity checks after an import or migration
+//!
+//! `rcommerce validate data` cross-checks what imports can get wrong:
+//! references to missing rows (bulk loads often run with foreign keys
+//! disabled), negative stock, order lines and totals that don't add up,
+//! tax transactions that don't match the order's tax, and currencies that
+//! differ between an order and its payments. Every issue comes with the IDs
+//! of a few offending rows. With `--fix` the classes that can be repaired
+//! without guessing (dropping rows nothing can reach, clearing references to
+//! missing rows, filling values the row itself determines) are repaired in
+//! one transaction; the rest are left for a person to decide.
+
+use colored::Colorize;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use super::doctor::{table_exists, CheckStatus};
+use rcommerce_core::Config;
+
+/// Offending row IDs shown per check
+const SAMPLE_SIZE: i64 = 10;
+
+/// A class of integrity problem
+struct DataCheck {
+    name: &'static str,
+    description: &'static str,
+    /// Error for broken data, warning for data that is only suspicious
+    severity: CheckStatus,
+    /// Selects `id` (text) of each offending row
+    query: &'static str,
+    /// Statements repairing every offending row; empty if it needs a person
+    repair: &'static [&'static str],
+    /// What to do when there is no automatic repair
+    hint: &'static str,
+    /// Tables of optional migrations the check reads; skipped without them
+    optional_tables: &'static [&'static str],
+}
+
+const CHECKS: &[DataCheck] = &[
+    DataCheck {
+        name: "orphaned_order_items",
+        description: "Order items of orders that don't exist",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT i.id::text AS id FROM order_items i
+            WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = i.order_id)
+        "#,
+        repair: &[r#"
+            DELETE FROM order_items i
+            WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = i.order_id)
+        "#],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "order_item_references",
+        description: "Order items referencing products or variants that don't exist",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT i.id::text AS id FROM order_items i
+            WHERE (i.product_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM products p WHERE p.id = i.product_id))
+               OR (i.variant_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.id = i.variant_id))
+        "#,
+        // What deleting the product would have done; the line keeps its title and SKU
+        repair: &[
+            r#"
+            UPDATE order_items i SET product_id = NULL, updated_at = NOW()
+            WHERE i.product_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM products p WHERE p.id = i.product_id)
+            "#,
+            r#"
+            UPDATE order_items i SET variant_id = NULL, updated_at = NOW()
+            WHERE i.variant_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.id = i.variant_id)
+            "#,
+        ],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "order_customers",
+        description: "Orders referencing customers that don't exist",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT o.id::text AS id FROM orders o
+            WHERE o.customer_id IS NOT NULL
+              AND NOT EXISTS (SELECT 1 FROM customers c WHERE c.id = o.customer_id)
+        "#,
+        // Re-link by email where an account matches, else unlink like a deleted customer
+        repair: &[
+            r#"
+            UPDATE orders o SET customer_id = c.id, updated_at = NOW()
+            FROM customers c
+            WHERE c.email = o.email AND o.customer_id IS NOT NULL
+              AND NOT EXISTS (SELECT 1 FROM customers x WHERE x.id = o.customer_id)
+            "#,
+            r#"
+            UPDATE orders o SET customer_id = NULL, updated_at = NOW()
+            WHERE o.customer_id IS NOT NULL
+              AND NOT EXISTS (SELECT 1 FROM customers c WHERE c.id = o.customer_id)
+            "#,
+        ],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "orphaned_variants",
+        description: "Variants of products that don't exist",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT v.id::text AS id FROM product_variants v
+            WHERE NOT EXISTS (SELECT 1 FROM products p WHERE p.id = v.product_id)
+        "#,
+        repair: &[],
+        hint: "Re-import the parent products, or delete the variants once nothing refers to them",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "orphaned_inventory_levels",
+        description: "Stock levels of products, variants or locations that don't exist",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT l.id::text AS id FROM inventory_levels l
+            WHERE NOT EXISTS (SELECT 1 FROM products p WHERE p.id = l.product_id)
+               OR (l.variant_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.id = l.variant_id))
+               OR NOT EXISTS (SELECT 1 FROM inventory_locations loc WHERE loc.id = l.location_id)
+        "#,
+        repair: &[r#"
+            DELETE FROM inventory_levels l
+            WHERE NOT EXISTS (SELECT 1 FROM products p WHERE p.id = l.product_id)
+               OR (l.variant_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.id = l.variant_id))
+               OR NOT EXISTS (SELECT 1 FROM inventory_locations loc WHERE loc.id = l.location_id)
+        "#],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "orphaned_payments",
+        description: "Payments and refunds of orders that don't exist",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT 'payment ' || p.id AS id FROM payments p
+            WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = p.order_id)
+            UNION ALL
+            SELECT 'refund ' || r.id FROM refunds r
+            WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = r.order_id)
+        "#,
+        repair: &[],
+        hint: "Money records are never deleted automatically; re-import the orders they belong to",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "orphaned_tax_transactions",
+        description: "Tax transactions of orders that don't exist",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT t.id::text AS id FROM tax_transactions t
+            WHERE NOT EXISTS (SELECT 1 FROM orders o WHERE o.id = t.order_id)
+        "#,
+        repair: &[],
+        hint: "Money records are never deleted automatically; re-import the orders they belong to",
+        optional_tables: &["tax_transactions"],
+    },
+    DataCheck {
+        name: "negative_stock",
+        description: "Negative stock on products that don't allow backorders",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT 'product ' || p.id AS id FROM products p
+            WHERE p.inventory_management AND p.inventory_quantity < 0
+              AND p.inventory_policy = 'deny' AND NOT p.continues_selling_when_out_of_stock
+            UNION ALL
+            SELECT 'variant ' || v.id FROM product_variants v
+            WHERE v.inventory_quantity < 0 AND v.inventory_policy = 'deny'
+            UNION ALL
+            SELECT 'inventory_level ' || l.id FROM inventory_levels l
+            WHERE l.available_quantity < 0
+        "#,
+        repair: &[],
+        hint: "Count the stock and record a stock adjustment, or allow backorders (inventory_policy = 'continue') where selling below zero is intended",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "negative_reservations",
+        description: "Stock levels with negative reserved or incoming quantities",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT l.id::text AS id FROM inventory_levels l
+            WHERE l.reserved_quantity < 0 OR l.incoming_quantity < 0
+        "#,
+        repair: &[r#"
+            UPDATE inventory_levels
+            SET reserved_quantity = GREATEST(reserved_quantity, 0),
+                incoming_quantity = GREATEST(incoming_quantity, 0),
+                updated_at = NOW()
+            WHERE reserved_quantity < 0 OR incoming_quantity < 0
+        "#],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "missing_item_subtotals",
+        description: "Order items imported without a subtotal although price, quantity and total agree",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT i.id::text AS id FROM order_items i
+            WHERE i.subtotal = 0 AND i.price * i.quantity <> 0
+              AND i.total = i.price * i.quantity + i.tax_amount
+        "#,
+        repair: &[r#"
+            UPDATE order_items SET subtotal = price * quantity, updated_at = NOW()
+            WHERE subtotal = 0 AND price * quantity <> 0
+              AND total = price * quantity + tax_amount
+        "#],
+        hint: "",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "item_totals",
+        description: "Order items whose subtotal isn't price × quantity or whose total isn't subtotal + tax",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT i.id::text AS id FROM order_items i
+            WHERE NOT (i.subtotal = 0 AND i.price * i.quantity <> 0 AND i.total = i.price * i.quantity + i.tax_amount)
+              AND (i.subtotal <> i.price * i.quantity OR i.total <> i.subtotal + i.tax_amount)
+        "#,
+        repair: &[],
+        hint: "Compare the lines with the source platform's order; which amount is right can't be told from here",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "order_subtotals",
+        description: "Orders whose subtotal isn't the sum of their lines and add-ons",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT o.id::text AS id FROM orders o
+            JOIN (
+                SELECT order_id, SUM(subtotal) AS lines FROM order_items
+                WHERE NOT COALESCE(is_bundle_component, false)
+                GROUP BY order_id
+            ) i ON i.order_id = o.id
+            LEFT JOIN (SELECT order_id, SUM(total) AS addons FROM order_addons GROUP BY order_id) a
+                ON a.order_id = o.id
+            WHERE o.subtotal <> i.lines + COALESCE(a.addons, 0)
+        "#,
+        repair: &[],
+        hint: "Compare with the source platform; imported orders may carry line discounts the lines don't show",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "order_totals",
+        description: "Orders whose total isn't subtotal + tax + shipping − discount + duties",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT o.id::text AS id FROM orders o
+            WHERE o.total <> o.subtotal + o.tax_total + o.shipping_total - o.discount_total
+                             + o.duty_total + o.import_tax_total
+        "#,
+        repair: &[],
+        hint: "Payments were taken for the stored total; correct the components rather than the total",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "orders_without_items",
+        description: "Placed (non-draft) orders without any items",
+        severity: CheckStatus::Warning,
+        query: r#"
+            SELECT o.id::text AS id FROM orders o
+            WHERE NOT o.draft AND NOT EXISTS (SELECT 1 FROM order_items i WHERE i.order_id = o.id)
+        "#,
+        repair: &[],
+        hint: "Re-import the order lines, or cancel the orders",
+        optional_tables: &[],
+    },
+    DataCheck {
+        name: "tax_transactions",
+        description: "Orders whose tax transactions don't match their line taxes or exceed their tax total",
+        severity: CheckStatus::Error,
+        // Shipping tax is part of tax_total but is not recorded as a transaction
+        query: r#"
+            SELECT o.id::text AS id FROM orders o
+            JOIN (SELECT order_id, ROUND(SUM(tax_amount), 2) AS recorded FROM tax_transactions GROUP BY order_id) t
+                ON t.order_id = o.id
+            LEFT JOIN (SELECT order_id, SUM(tax_amount) AS tax FROM order_items GROUP BY order_id) i
+                ON i.order_id = o.id
+            LEFT JOIN (SELECT order_id, SUM(tax_amount) AS tax FROM order_addons GROUP BY order_id) a
+                ON a.order_id = o.id
+            WHERE t.recorded > o.tax_total + 0.01
+               OR ABS(t.recorded - COALESCE(i.tax, 0) - COALESCE(a.tax, 0)) > 0.01
+        "#,
+        repair: &[],
+        hint: "Tax reports are built from tax transactions; recalculate them from the source platform's tax lines",
+        optional_tables: &["tax_transactions"],
+    },
+    DataCheck {
+        name: "currency_mismatch",
+        description: "Payments or refunds in another currency than their order, variants in another currency than their product",
+        severity: CheckStatus::Error,
+        query: r#"
+            SELECT 'payment ' || p.id AS id FROM payments p
+            JOIN orders o ON o.id = p.order_id WHERE p.currency <> o.currency
+            UNION ALL
+            SELECT 'refund ' || r.id FROM refunds r
+            JOIN orders o ON o.id = r.order_id WHERE r.currency <> o.currency
+            UNION ALL
+            SELECT 'variant ' || v.id FROM product_variants v
+            JOIN products p ON p.id = v.product_id WHERE v.currency <> p.currency
+        "#,
+        repair: &[],
+        hint: "Amounts can't be converted after the fact; re-import with the currency the amounts were charged in",
+        optional_tables: &[],
+    },
+];
+
+/// Outcome of one check
+#[derive(Debug, Clone, Serialize)]
+pub struct DataIssue {
+    pub name: String,
+    pub description: String,
+    pub status: CheckStatus,
+    /// Offending rows found
+    pub count: i64,
+    /// A few of their IDs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sample_ids: Vec<String>,
+    /// Whether `--fix` can repair them
+    pub repairable: bool,
+    /// Rows changed by `--fix`
+    pub repaired: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+/// Full validation report
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub status: CheckStatus,
+    pub issues: Vec<DataIssue>,
+}
+
+impl ValidationReport {
+    fn new(issues: Vec<DataIssue>) -> Self {
+        let status = issues
+            .iter()
+            .map(|issue| issue.status)
+            .filter(|status| *status != CheckStatus::Skipped)
+            .max()
+            .unwrap_or(CheckStatus::Ok);
+        Self { checked_at: chrono::Utc::now(), status, issues }
+    }
+
+    /// Whether any errors remain (after repairs)
+    pub fn has_errors(&self) -> bool {
+        self.status == CheckStatus::Error
+    }
+}
+
+/// Names of all checks, for `--check`
+pub fn check_names() -> Vec<&'static str> {
+    CHECKS.iter().map(|check| check.name).collect()
+}
+
+/// Run the checks (all, or those named in `only`), repair the safe classes
+/// with `fix`, and print the report
+pub async fn run_validate(config: &Config, only: &[String], fix: bool, json: bool) -> Result<ValidationReport, String> {
+    if let Some(unknown) = only.iter().find(|name| !CHECKS.iter().any(|check| check.name == name.as_str())) {
+        return Err(format!("Unknown check '{}'; checks: {}", unknown, check_names().join(", ")));
+    }
+    let checks: Vec<&DataCheck> = CHECKS
+        .iter()
+        .filter(|check| only.is_empty() || only.iter().any(|name| name == check.name))
+        .collect();
+
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    let mut issues = Vec::with_capacity(checks.len());
+    for check in &checks {
+        issues.push(run_check(&pool, check).await);
+    }
+
+    if fix {
+        let repairs: Vec<(&DataCheck, &mut DataIssue)> = checks
+            .iter()
+            .copied()
+            .zip(issues.iter_mut())
+            .filter(|(check, issue)| !check.repair.is_empty() && issue.count > 0)
+            .collect();
+        repair(&pool, repairs).await?;
+    }
+
+    let report = ValidationReport::new(issues);
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize report: {}", e))?;
+        println!("{}", output);
+    } else {
+        print_report(&report, fix);
+    }
+    Ok(report)
+}
+
+async fn run_check(pool: &PgPool, check: &DataCheck) -> DataIssue {
+    let sql = format!(
+        "SELECT q.id, COUNT(*) OVER () FROM ({}) q ORDER BY q.id LIMIT {}",
+        check.query, SAMPLE_SIZE
+    );
+    let mut issue = DataIssue {
+        name: check.name.to_string(),
+        description: check.description.to_string(),
+        status: CheckStatus::Ok,
+        count: 0,
+        sample_ids: Vec::new(),
+        repairable: !check.repair.is_empty(),
+        repaired: 0,
+        hint: None,
+    };
+
+    for table in check.optional_tables {
+        if !table_exists(pool, table).await {
+            issue.status = CheckStatus::Skipped;
+            issue.hint = Some(format!("No {} table; it comes from an optional migration", table));
+            return issue;
+        }
+    }
+
+    match sqlx::query_as::<_, (String, i64)>(&sql).fetch_all(pool).await {
+        Ok(rows) => {
+            issue.count = rows.first().map(|(_, count)| *count).unwrap_or(0);
+            issue.sample_ids = rows.into_iter().map(|(id, _)| id).collect();
+            if issue.count > 0 {
+                issue.status = check.severity;
+                issue.hint = Some(if issue.repairable {
+                    "Repairable with --fix".to_string()
+                } else {
+                    check.hint.to_string()
+                });
+            }
+        }
+        Err(e) => {
+            issue.status = CheckStatus::Error;
+            issue.hint = Some(format!("Query failed ({}); run 'rcommerce db migrate'", e));
+        }
+    }
+    issue
+}
+
+/// Apply the repairs in one transaction; repaired issues drop to ok
+async fn repair(pool: &PgPool, repairs: Vec<(&DataCheck, &mut DataIssue)>) -> Result<(), String> {
+    if repairs.is_empty() {
+        return Ok(());
+    }
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut repaired = Vec::with_capacity(repairs.len());
+    for (check, issue) in repairs {
+        let mut rows = 0;
+        for statement in check.repair {
+            rows += sqlx::query(statement)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Repair of {} failed: {}", check.name, e))?
+                .rows_affected();
+        }
+        repaired.push((issue, rows));
+    }
+    tx.commit().await.map_err(|e| format!("Failed to commit repairs: {}", e))?;
+
+    for (issue, rows) in repaired {
+        issue.repaired = rows;
+        issue.status = CheckStatus::Ok;
+        issue.hint = None;
+    }
+    Ok(())
+}
+
+fn print_report(report: &ValidationReport, fix: bool) {
+    println!("{}", "R Commerce Data Validation".bold().underline());
+    println!();
+
+    for issue in &report.issues {
+        let label = match issue.status {
+            CheckStatus::Ok => "✓ ok     ".green(),
+            CheckStatus::Skipped => "- skipped".dimmed(),
+            CheckStatus::Warning => "⚠ warning".yellow(),
+            CheckStatus::Error => "✗ error  ".red(),
+        };
+        let message = if issue.repaired > 0 {
+            format!("{} ({} found, {} rows repaired)", issue.description, issue.count, issue.repaired)
+        } else if issue.count > 0 {
+            format!("{} ({} found)", issue.description, issue.count)
+        } else {
+            issue.description.clone()
+        };
+        println!("{} {:<26} {}", label, issue.name.bold(), message);
+        if issue.status != CheckStatus::Ok && !issue.sample_ids.is_empty() {
+            println!("{:>38} {}", "e.g.".cyan(), issue.sample_ids.join(", "));
+        }
+        if let Some(ref hint) = issue.hint {
+            println!("{:>38} {}", "fix:".cyan(), hint);
+        }
+    }
+
+    println!();
+    let open = |status: CheckStatus| report.issues.iter().filter(|issue| issue.status == status).count();
+    let repairable = report.issues.iter().filter(|issue| issue.repairable && issue.count > 0 && issue.repaired == 0).count();
+    let summary = format!("{} checks, {} with errors, {} with warnings", report.issues.len(), open(CheckStatus::Error), open(CheckStatus::Warning));
+    match report.status {
+        CheckStatus::Error => println!("{}", format!("❌ {}", summary).red().bold()),
+        CheckStatus::Warning => println!("{}", format!("⚠️  {}", summary).yellow().bold()),
+        _ => println!("{}", format!("✅ {}", summary).green().bold()),
+    }
+    if !fix && repairable > 0 {
+        println!("Run with --fix to repair {} of them", repairable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_names_are_unique() {
+        let mut names = check_names();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), CHECKS.len());
+    }
+
+    #[test]
+    fn test_only_hinted_checks_need_a_person() {
+        for check in CHECKS {
+            assert_ne!(check.repair.is_empty(), check.hint.is_empty(), "{}", check.name);
+            assert!(check.query.contains(" AS id "), "{} must select an id column", check.name);
+        }
+    }
+
+    #[test]
+    fn test_report_status_is_worst_issue() {
+        let issue = |status| DataIssue {
+            name: "a".to_string(),
+            description: String::new(),
+            status,
+            count: 0,
+            sample_ids: Vec::new(),
+            repairable: false,
+            repaired: 0,
+            hint: None,
+        };
+        let report = ValidationReport::new(vec![issue(CheckStatus::Ok), issue(CheckStatus::Warning)]);
+        assert_eq!(report.status, CheckStatus::Warning);
+        assert_eq!(ValidationReport::new(vec![issue(CheckStatus::Ok), issue(CheckStatus::Skipped)]).status, CheckStatus::Ok);
+        assert!(!report.has_errors());
+        assert!(ValidationReport::new(vec![issue(CheckStatus::Error)]).has_errors());
+    }
+}
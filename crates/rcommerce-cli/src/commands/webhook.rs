@@ -0,0 +1,67 @@
+//! Replay outbound webhook events to an endpoint
+//!
+//! Re-sends the events originally delivered to a webhook endpoint in a time
+//! range, e.g. after the consumer was down, and prints progress as it goes.
+//! The replay is recorded like one started from
+//! `POST /api/v1/admin/webhooks/:id/replays`, so it also shows up there.
+
+use colored::Colorize;
+use std::io::Write;
+use uuid::Uuid;
+
+use rcommerce_core::models::{CreateWebhookReplayRequest, WebhookReplayStatus};
+use rcommerce_core::repository::PostgresWebhookReplayRepository;
+use rcommerce_core::secrets::SecretBox;
+use rcommerce_core::services::WebhookReplayService;
+use rcommerce_core::Config;
+
+/// Replay events and return the number of failed deliveries
+pub async fn run_webhook_replay(
+    config: &Config,
+    webhook_id: Uuid,
+    request: CreateWebhookReplayRequest,
+) -> Result<i32, String> {
+    let pool = crate::create_pool(config).await.map_err(|e| e.to_string())?;
+    let secrets = SecretBox::from_config(&config.secrets).map_err(|e| e.to_string())?;
+    let replays = WebhookReplayService::new(
+        PostgresWebhookReplayRepository::new(pool),
+        config.webhook_replay.clone(),
+    )
+    .with_secrets(secrets);
+
+    if request.dry_run {
+        let events = replays.preview(webhook_id, &request).await.map_err(|e| e.to_string())?;
+        println!("{} events would be replayed", events);
+        return Ok(0);
+    }
+
+    let replay = replays
+        .create(webhook_id, &request, Some("cli"))
+        .await
+        .map_err(|e| e.to_string())?;
+    println!(
+        "Replaying {} events to webhook {} at {}/s (replay {})",
+        replay.total_events, webhook_id, replay.rate_per_second, replay.id
+    );
+
+    let replay = replays
+        .run_with_progress(replay.id, |r| {
+            print!("\r  {}/{} sent, {} failed", r.sent, r.total_events, r.failed);
+            let _ = std::io::stdout().flush();
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    println!();
+
+    match replay.status {
+        WebhookReplayStatus::Completed => println!(
+            "{}",
+            format!("✅ Replayed {} events ({} failed)", replay.sent, replay.failed).green()
+        ),
+        WebhookReplayStatus::Cancelled => println!("{}", format!("Replay cancelled after {} events", replay.sent).yellow()),
+        _ => {
+            return Err(replay.error_message.unwrap_or_else(|| format!("replay ended as {:?}", replay.status)));
+        }
+    }
+    Ok(replay.failed)
+}
@@ -5,11 +5,24 @@ use std::path::PathBuf;
 use tracing::info;
 
 use rcommerce_core::{Result, Config};
-use rcommerce_core::models::{ProductType, Currency};
+use rcommerce_core::models::{ProductType, Currency, SoftDeleteKind};
 
 mod commands {
+    pub mod config_bundle;
+    pub mod demo;
+    pub mod doctor;
+    pub mod email_theme;
+    pub mod export;
+    pub mod jobs;
+    pub mod promote;
+    pub mod replay;
+    pub mod report;
+    pub mod secrets;
     pub mod setup;
     pub mod shell;
+    pub mod shipping;
+    pub mod validate;
+    pub mod webhook;
 }
 
 /// Security checks for CLI operations
@@ -96,6 +109,15 @@ pub enum Commands {
         skip_migrate: bool,
     },
     
+    /// Run recurring jobs and background tasks without serving HTTP
+    Worker {
+        #[arg(long, help = "Jobs run at once (default: scheduler.concurrency)")]
+        concurrency: Option<usize>,
+        
+        #[arg(long, help = "Seconds to wait for running jobs on shutdown (default: scheduler.drain_timeout_secs)")]
+        drain_timeout: Option<u64>,
+    },
+    
     /// Database operations
     Db {
         #[command(subcommand)]
@@ -132,14 +154,38 @@ pub enum Commands {
         command: ImportCommands,
     },
     
+    /// Export products, customers or orders as CSV or JSON
+    Export {
+        /// What to export (products, customers, orders)
+        entity: rcommerce_core::models::ExportEntity,
+        
+        #[arg(short, long, help = "Output format (csv, json)", default_value = "csv")]
+        format: rcommerce_core::models::ExportFormat,
+        
+        #[arg(short, long, help = "Output file (default: stdout)")]
+        output: Option<PathBuf>,
+        
+        #[arg(long, help = "Only records created at or after this time (RFC 3339)")]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        
+        #[arg(long, help = "Only records created before this time (RFC 3339)")]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        
+        #[arg(long, help = "Only records with this status (e.g. completed, active, verified)")]
+        status: Option<String>,
+    },
+    
     /// TLS certificate management
     Tls {
         #[command(subcommand)]
         command: TlsCommands,
     },
     
-    /// Show configuration
-    Config,
+    /// Show configuration, or export and import the store configuration
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
+    },
     
     /// Email testing and management
     Email {
@@ -156,6 +202,141 @@ pub enum Commands {
     
     /// Interactive shell for managing your R Commerce installation
     Shell,
+    
+    /// Diagnose common installation problems
+    Doctor {
+        #[arg(long, help = "Output machine-readable JSON")]
+        json: bool,
+    },
+    
+    /// Check data integrity, e.g. after an import or migration
+    Validate {
+        #[command(subcommand)]
+        command: ValidateCommands,
+    },
+    
+    /// Webhook management
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommands,
+    },
+    
+    /// Master key management for secrets stored in the database
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsCommands,
+    },
+    
+    /// Recurring job management
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommands,
+    },
+    
+    /// Sales report summaries
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    
+    /// Shipping and warehouse print station tools
+    Shipping {
+        #[command(subcommand)]
+        command: ShippingCommands,
+    },
+    
+    /// Replay captured traffic against another server
+    Replay {
+        /// Capture file downloaded from /api/v1/admin/capture
+        file: PathBuf,
+        
+        #[arg(short, long, help = "Target base URL (e.g. https://staging.example.com)")]
+        target: String,
+        
+        #[arg(long, help = "Bearer token for the target (captured credentials are redacted)")]
+        token: Option<String>,
+        
+        #[arg(long, help = "Only replay requests whose path starts with this prefix")]
+        path_prefix: Option<String>,
+        
+        #[arg(long, help = "Also replay POST/PUT/PATCH/DELETE requests")]
+        include_writes: bool,
+        
+        #[arg(long, help = "Delay between requests in milliseconds", default_value = "0")]
+        delay_ms: u64,
+        
+        #[arg(long, help = "List requests without sending them")]
+        dry_run: bool,
+    },
+    
+    /// Write the OpenAPI document of the storefront API (for client generators)
+    Openapi {
+        #[arg(short, long, help = "Output file (default: stdout)")]
+        output: Option<PathBuf>,
+    },
+    
+    /// Write the JSON Schemas of domain events and webhook payloads (for type generators)
+    EventSchema {
+        #[arg(short, long, help = "Output file (default: stdout)")]
+        output: Option<PathBuf>,
+    },
+    
+    /// Promote catalog changes (products, categories, tax, shipping rules) between instances
+    Promote {
+        #[arg(long, help = "Source base URL (e.g. https://staging.example.com)")]
+        source: String,
+        
+        #[arg(long, env = "RCOMMERCE_SOURCE_TOKEN", help = "Admin bearer token for the source")]
+        source_token: String,
+        
+        #[arg(short, long, help = "Target base URL (e.g. https://shop.example.com)")]
+        target: String,
+        
+        #[arg(long, env = "RCOMMERCE_TARGET_TOKEN", help = "Admin bearer token for the target")]
+        target_token: String,
+        
+        #[arg(long = "entity", help = "Only promote this entity: product, category, tax_zone, tax_category, tax_rate, shipping_rule (repeatable)")]
+        entities: Vec<rcommerce_core::models::CatalogEntity>,
+        
+        #[arg(long = "delete", help = "Also delete target records missing from the source (products are deactivated)")]
+        include_deletes: bool,
+        
+        #[arg(long, help = "Show the changes without applying them")]
+        dry_run: bool,
+        
+        #[arg(short, long, help = "Apply every change without asking")]
+        yes: bool,
+    },
+    
+    /// Run a sample store locally
+    Demo {
+        #[command(subcommand)]
+        command: DemoCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DemoCommands {
+    /// Migrate and seed the database, create the frontend's API key and run the API and demo frontend
+    Up {
+        #[arg(long, help = "API port", default_value = "8080")]
+        api_port: u16,
+        
+        #[arg(long, help = "Frontend port", default_value = "3000")]
+        frontend_port: u16,
+        
+        #[arg(long, help = "Frontend server binary (default: rcommerce-frontend next to this binary, else on the PATH)")]
+        frontend_bin: Option<PathBuf>,
+        
+        #[arg(long, help = "Demo frontend templates and static files", default_value = "demo-frontend")]
+        frontend_dir: PathBuf,
+        
+        #[arg(long, help = "Don't seed the sample catalog")]
+        skip_seed: bool,
+        
+        #[arg(long, help = "Run only the API")]
+        no_frontend: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -174,6 +355,210 @@ pub enum DbCommands {
     
     /// Show database status
     Status,
+
+    /// Create upcoming monthly partitions and list all partitions
+    Partitions {
+        #[arg(long, default_value_t = rcommerce_core::PARTITION_MONTHS_AHEAD, help = "Months ahead of the current one to create")]
+        months_ahead: i32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsCommands {
+    /// Print a new master key
+    GenerateKey,
+    
+    /// Re-encrypt stored secrets under the current master key
+    Rotate {
+        #[arg(long, help = "Count the secrets to re-encrypt without changing them")]
+        dry_run: bool,
+    },
+    
+    /// Show the master key in use and how many secrets need re-encrypting
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ValidateCommands {
+    /// Cross-check references, stock, order totals, tax and currencies
+    Data {
+        #[arg(long, help = "Repair the issue classes that are safe to repair automatically")]
+        fix: bool,
+        
+        #[arg(long = "check", help = "Only run this check (repeatable)")]
+        checks: Vec<String>,
+        
+        #[arg(long, help = "Output machine-readable JSON")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JobsCommands {
+    /// List jobs with their schedules and last runs
+    List {
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    
+    /// Run a job once on the next scheduler poll, even if paused
+    Run {
+        /// Job name
+        name: String,
+    },
+    
+    /// Stop scheduled runs of a job
+    Pause {
+        /// Job name
+        name: String,
+    },
+    
+    /// Resume scheduled runs of a paused job
+    Resume {
+        /// Job name
+        name: String,
+    },
+    
+    /// Change a job's cron schedule
+    Schedule {
+        /// Job name
+        name: String,
+        
+        /// Cron expression, e.g. "*/15 * * * *" or "0 3 * * MON-FRI"
+        expression: String,
+    },
+    
+    /// List jobs that failed every retry (the dead-letter queue)
+    Failed {
+        #[arg(long, help = "Include retried and discarded entries")]
+        all: bool,
+        
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    
+    /// Run a dead-lettered job again on the next scheduler poll
+    Retry {
+        /// Dead letter ID, as shown by `jobs failed`
+        id: uuid::Uuid,
+    },
+    
+    /// Discard a dead-lettered job without running it again
+    Discard {
+        /// Dead letter ID, as shown by `jobs failed`
+        id: uuid::Uuid,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// Revenue, refunds, average order value, channels and top products
+    Summary {
+        #[arg(long, help = "First day (YYYY-MM-DD)")]
+        from: Option<chrono::NaiveDate>,
+        
+        #[arg(long, help = "Last day (YYYY-MM-DD, default today)")]
+        to: Option<chrono::NaiveDate>,
+        
+        #[arg(long, default_value = "30", help = "Days covered unless both --from and --to are given")]
+        days: i64,
+        
+        #[arg(long, default_value = "day", help = "Period length: day, week or month")]
+        granularity: rcommerce_core::reports::Granularity,
+        
+        #[arg(long, default_value = "5", help = "Top products shown")]
+        top: i64,
+        
+        #[arg(long, help = "Refresh the sales figures first")]
+        refresh: bool,
+        
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    
+    /// Refresh the sales figures now instead of waiting for the sales_views_refresh job
+    Refresh,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Write tax, shipping rule, email template and payment settings as a YAML bundle
+    Export {
+        #[arg(short, long, help = "Bundle path (default stdout)")]
+        output: Option<PathBuf>,
+    },
+    
+    /// Apply a YAML bundle written by `config export`
+    Import {
+        /// Bundle file
+        file: PathBuf,
+        
+        #[arg(long = "delete", help = "Also delete tax and shipping records missing from the bundle")]
+        include_deletes: bool,
+        
+        #[arg(long, help = "Show the changes without applying them")]
+        dry_run: bool,
+        
+        #[arg(short, long, help = "Apply without asking")]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ShippingCommands {
+    /// Merge shipping labels or packing slips into one PDF for a printer
+    PrintBatch {
+        #[arg(long = "shipment", help = "Shipment to print (repeatable)")]
+        shipments: Vec<uuid::Uuid>,
+        
+        #[arg(long, conflicts_with = "shipments", help = "Print every shipment whose label has not been printed yet")]
+        unprinted: bool,
+        
+        #[arg(long, requires = "unprinted", help = "Only shipments leaving from this location code")]
+        location: Option<String>,
+        
+        #[arg(long, help = "Most shipments to print (default printing.max_batch_size)")]
+        limit: Option<usize>,
+        
+        #[arg(long, default_value = "labels", help = "Documents to print: labels or packing_slips")]
+        documents: rcommerce_core::models::PrintDocumentKind,
+        
+        #[arg(long, help = "Printer profile (default printing.label_profile or printing.document_profile)")]
+        profile: Option<String>,
+        
+        #[arg(short, long, help = "PDF path (default print-batch-<id>.pdf)")]
+        output: Option<PathBuf>,
+        
+        #[arg(long, help = "Send the PDF to the profile's print_command")]
+        print: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WebhookCommands {
+    /// Re-send a webhook endpoint's events from a time range
+    Replay {
+        /// Webhook ID
+        webhook_id: uuid::Uuid,
+        
+        #[arg(long, help = "Start of the range (RFC 3339, e.g. 2026-10-01T00:00:00Z)")]
+        from: chrono::DateTime<chrono::Utc>,
+        
+        #[arg(long, help = "End of the range (RFC 3339)")]
+        to: chrono::DateTime<chrono::Utc>,
+        
+        #[arg(long = "event", help = "Only replay this event type (repeatable)")]
+        events: Vec<String>,
+        
+        #[arg(long, help = "Skip events the endpoint already acknowledged")]
+        failed_only: bool,
+        
+        #[arg(long, help = "Deliveries per second (default from [webhook_replay])")]
+        rate: Option<u32>,
+        
+        #[arg(long, help = "Only count the events that would be replayed")]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -196,11 +581,17 @@ pub enum ProductCommands {
         id: String,
     },
     
-    /// Delete a product
+    /// Delete a product (restorable until [soft_delete] retention_days pass)
     Delete {
         #[arg(help = "Product ID")]
         id: String,
     },
+    
+    /// Restore a deleted product
+    Restore {
+        #[arg(help = "Product ID")]
+        id: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -208,6 +599,12 @@ pub enum OrderCommands {
     /// List orders
     List,
     
+    /// Find orders by order number
+    Find {
+        #[arg(help = "Order number or part of one, e.g. INV-2026-")]
+        number: String,
+    },
+    
     /// Get order details
     Get {
         #[arg(help = "Order ID")]
@@ -222,6 +619,19 @@ pub enum OrderCommands {
         #[arg(help = "Order ID")]
         id: String,
     },
+    
+    /// Move finished orders older than [order_archive] archive_after_days into the archive
+    Archive {
+        /// Only count the orders that would be archived
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Restore a deleted order
+    Restore {
+        #[arg(help = "Order ID")]
+        id: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -237,6 +647,18 @@ pub enum CustomerCommands {
     
     /// Create a customer
     Create,
+    
+    /// Delete a customer (restorable until [soft_delete] retention_days pass)
+    Delete {
+        #[arg(help = "Customer ID")]
+        id: String,
+    },
+    
+    /// Restore a deleted customer
+    Restore {
+        #[arg(help = "Customer ID")]
+        id: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -278,6 +700,14 @@ pub enum ImportCommands {
         /// Default currency for imported records (ISO 4217 code)
         #[arg(short = 'C', long, help = "Default currency code (USD, AUD, EUR, etc.)", default_value = "USD")]
         currency: String,
+        
+        /// Only import records changed after this time, updating existing ones
+        #[arg(long, help = "Only import records changed since this time (RFC 3339 or YYYY-MM-DD)")]
+        since: Option<String>,
+        
+        /// Only import records changed since the last sync, updating existing ones
+        #[arg(long, help = "Only import records changed since the last successful sync")]
+        incremental: bool,
     },
     
     /// Import from a file (csv, json, xml)
@@ -340,7 +770,7 @@ pub enum EmailCommands {
     
     /// Test a specific email template
     Test {
-        #[arg(help = "Template type (order_confirmation, order_shipped, payment_failed, payment_successful, subscription_created, subscription_renewal, subscription_cancelled, dunning_first, dunning_retry, dunning_final, welcome, password_reset, abandoned_cart)")]
+        #[arg(help = "Template type (order_confirmation, order_shipped, payment_failed, payment_successful, return_approved, return_rejected, subscription_created, subscription_renewal, subscription_cancelled, dunning_first, dunning_retry, dunning_final, welcome, password_reset, abandoned_cart)")]
         template: String,
         
         #[arg(short, long, help = "Output directory for test email", default_value = "./test-emails")]
@@ -364,6 +794,27 @@ pub enum EmailCommands {
     
     /// List all available email templates
     List,
+    
+    /// List the variables each template can use, with sample values
+    Variables {
+        #[arg(help = "Template type (all templates if omitted)")]
+        template: Option<String>,
+        
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    
+    /// Check template overrides for syntax errors and unknown or missing variables
+    Validate {
+        #[arg(short, long, help = "Templates directory (defaults to notifications.email.templates_dir)")]
+        dir: Option<PathBuf>,
+        
+        #[arg(long, help = "Skip the templates stored in the database")]
+        skip_db: bool,
+        
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -382,11 +833,32 @@ pub enum ApiKeyCommands {
         #[arg(short = 'n', long, help = "Key name/description")]
         name: Option<String>,
         
-        #[arg(short = 's', long, help = "Scopes (comma-separated)", default_value = "read")]
+        #[arg(short = 's', long, help = "Scopes (comma-separated, ignored for publishable keys)", default_value = "read")]
         scopes: String,
         
         #[arg(short = 'e', long, help = "Expiration in days (optional)")]
         expires_days: Option<i64>,
+        
+        #[arg(long, help = "Create a publishable storefront key (read-only storefront routes)")]
+        publishable: bool,
+        
+        #[arg(long, value_delimiter = ',', help = "Allowed origins for a publishable key (comma-separated, e.g. https://shop.example.com,*.example.com)")]
+        origins: Vec<String>,
+        
+        #[arg(long, help = "Requests per minute for this key (overrides the configured default)")]
+        rate_limit: Option<i32>,
+        
+        #[arg(long, value_delimiter = ',', help = "Only accept the key from these addresses or CIDR networks (comma-separated, e.g. 203.0.113.7,10.0.0.0/8)")]
+        allow_ips: Vec<String>,
+    },
+    
+    /// Replace an API key with a new one carrying the same settings
+    Rotate {
+        #[arg(help = "Key prefix")]
+        prefix: String,
+        
+        #[arg(long, help = "Hours the old key keeps working", default_value = "24")]
+        grace_hours: i64,
     },
     
     /// Get API key details
@@ -462,12 +934,27 @@ async fn main() -> Result<()> {
             rcommerce_api::run(config).await?;
         }
         
+        Commands::Worker { concurrency, drain_timeout } => {
+            let mut config = config;
+            if let Some(concurrency) = concurrency {
+                if concurrency == 0 {
+                    eprintln!("❌ --concurrency must be at least 1");
+                    std::process::exit(1);
+                }
+                config.scheduler.concurrency = concurrency;
+            }
+            if let Some(drain_timeout) = drain_timeout {
+                config.scheduler.drain_timeout_secs = drain_timeout;
+            }
+            rcommerce_api::run_worker(config).await?;
+        }
+        
         Commands::Db { command } => {
             use colored::*;
             
             // Create database pool
             let pool = create_pool(&config).await?;
-            let migrator = rcommerce_core::Migrator::new(pool);
+            let migrator = rcommerce_core::Migrator::new(pool.clone());
             
             match command {
                 DbCommands::Migrate => {
@@ -528,6 +1015,7 @@ async fn main() -> Result<()> {
                             println!("  Host: {}:{}", config.database.host, config.database.port);
                             println!("  Database: {}", config.database.database);
                             println!("  Applied migrations: {}", status.applied_migrations);
+                            println!("  Schema version: {}", status.schema_version);
                             println!("  Products: {}", status.product_count);
                             println!("  Customers: {}", status.customer_count);
                             println!("  Orders: {}", status.order_count);
@@ -538,6 +1026,28 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                DbCommands::Partitions { months_ahead } => {
+                    let manager = rcommerce_core::PartitionManager::new(pool);
+                    let result = match manager.ensure_partitions(months_ahead).await {
+                        Ok(created) => manager.list_partitions().await.map(|partitions| (created, partitions)),
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok((created, partitions)) => {
+                            for name in &created {
+                                println!("{}", format!("Created {}", name).green());
+                            }
+                            println!("{}", "Table Partitions".bold().underline());
+                            for p in &partitions {
+                                println!("  {:<32} {:<24} ~{} rows  {}", p.partition, p.parent, p.estimated_rows.max(0), p.bounds);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{}", format!("❌ Partition maintenance failed: {}", e).red());
+                            std::process::exit(1);
+                        }
+                    }
+                }
             }
         }
         
@@ -636,14 +1146,10 @@ async fn main() -> Result<()> {
                         return Ok(());
                     }
                     
-                    match delete_product(&pool, &id).await {
-                        Ok(true) => println!("{}", format!("✅ Product '{}' deleted", id).green()),
-                        Ok(false) => println!("{}", format!("Product '{}' not found", id).yellow()),
-                        Err(e) => {
-                            eprintln!("{}", format!("❌ Failed to delete product: {}", e).red());
-                            std::process::exit(1);
-                        }
-                    }
+                    soft_delete_record(&pool, &config, SoftDeleteKind::Products, &id, false).await;
+                }
+                ProductCommands::Restore { id } => {
+                    soft_delete_record(&pool, &config, SoftDeleteKind::Products, &id, true).await;
                 }
             }
         }
@@ -657,42 +1163,45 @@ async fn main() -> Result<()> {
             let pool = create_pool(&config).await?;
             
             match command {
-                OrderCommands::List => {
-                    match list_orders(&pool).await {
-                        Ok(orders) => {
-                            if orders.is_empty() {
-                                println!("{}", "No orders found".yellow());
+                OrderCommands::List => print_orders(&pool, None).await,
+                OrderCommands::Find { number } => print_orders(&pool, Some(number.trim())).await,
+                OrderCommands::Get { id } => {
+                    println!("{}", format!("Order details for '{}' coming soon!", id).yellow());
+                }
+                OrderCommands::Create => {
+                    println!("{}", "Order creation via CLI coming soon!".yellow());
+                }
+                OrderCommands::Update { id } => {
+                    println!("{}", format!("Order update for '{}' coming soon!", id).yellow());
+                }
+                OrderCommands::Archive { dry_run } => {
+                    use rcommerce_core::repository::PostgresOrderArchiveRepository;
+                    use rcommerce_core::services::OrderArchiveService;
+                    
+                    let archiver = OrderArchiveService::new(
+                        PostgresOrderArchiveRepository::new(pool.clone()),
+                        config.order_archive.clone(),
+                    );
+                    match archiver.run(dry_run).await {
+                        Ok(report) => {
+                            let cutoff = report.cutoff.map(|c| c.format("%Y-%m-%d").to_string()).unwrap_or_default();
+                            if dry_run {
+                                println!("{} orders finished before {} would be archived", report.eligible, cutoff);
                             } else {
-                                println!("{}", "Orders".bold().underline());
-                                println!("{:<36} {:<20} {:<12} {:<15} {:<12}", 
-                                    "ID", "Customer", "Status", "Total", "Created");
-                                println!("{}", "-".repeat(100));
-                                for o in &orders {
-                                    println!("{:<36} {:<20} {:<12} {:<15.2} {:<12}",
-                                        o.id.to_string(),
-                                        truncate(&o.customer_email, 18),
-                                        o.status,
-                                        o.total,
-                                        o.created_at.format("%Y-%m-%d")
-                                    );
+                                println!("{}", format!("✅ Archived {} orders finished before {}", report.archived, cutoff).green());
+                                if let Some(file) = report.export_file {
+                                    println!("   Exported to {}", file);
                                 }
-                                println!("\nTotal: {} orders", orders.len());
                             }
                         }
                         Err(e) => {
-                            eprintln!("{}", format!("❌ Failed to list orders: {}", e).red());
+                            eprintln!("{}", format!("❌ Failed to archive orders: {}", e).red());
                             std::process::exit(1);
                         }
                     }
                 }
-                OrderCommands::Get { id } => {
-                    println!("{}", format!("Order details for '{}' coming soon!", id).yellow());
-                }
-                OrderCommands::Create => {
-                    println!("{}", "Order creation via CLI coming soon!".yellow());
-                }
-                OrderCommands::Update { id } => {
-                    println!("{}", format!("Order update for '{}' coming soon!", id).yellow());
+                OrderCommands::Restore { id } => {
+                    soft_delete_record(&pool, &config, SoftDeleteKind::Orders, &id, true).await;
                 }
             }
         }
@@ -751,6 +1260,12 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                CustomerCommands::Delete { id } => {
+                    soft_delete_record(&pool, &config, SoftDeleteKind::Customers, &id, false).await;
+                }
+                CustomerCommands::Restore { id } => {
+                    soft_delete_record(&pool, &config, SoftDeleteKind::Customers, &id, true).await;
+                }
             }
         }
         
@@ -768,15 +1283,16 @@ async fn main() -> Result<()> {
                                 println!("{}", "No API keys found".yellow());
                             } else {
                                 println!("{}", "API Keys".bold().underline());
-                                println!("{:<12} {:<20} {:<30} {:<10} {:<12}", 
-                                    "Prefix", "Name", "Scopes", "Active", "Expires");
-                                println!("{}", "-".repeat(90));
+                                println!("{:<12} {:<12} {:<20} {:<30} {:<10} {:<12}", 
+                                    "Prefix", "Type", "Name", "Scopes", "Active", "Expires");
+                                println!("{}", "-".repeat(103));
                                 for key in keys {
                                     let expires = key.expires_at
                                         .map(|d| d.format("%Y-%m-%d").to_string())
                                         .unwrap_or_else(|| "Never".to_string());
-                                    println!("{:<12} {:<20} {:<30} {:<10} {:<12}",
+                                    println!("{:<12} {:<12} {:<20} {:<30} {:<10} {:<12}",
                                         key.key_prefix,
+                                        key.key_type.as_str(),
                                         key.name,
                                         key.scopes.join(", "),
                                         if key.is_active { "✓".green() } else { "✗".red() },
@@ -792,10 +1308,11 @@ async fn main() -> Result<()> {
                     }
                 }
                 
-                ApiKeyCommands::Create { customer_id, name, scopes, expires_days } => {
+                ApiKeyCommands::Create { customer_id, name, scopes, expires_days, publishable, origins, rate_limit, allow_ips } => {
                     let auth_service = rcommerce_core::services::AuthService::new(config);
+                    let options = NewApiKey { customer_id, name, scopes, expires_days, publishable, origins, rate_limit, allow_ips };
                     
-                    match create_api_key(&pool, &auth_service, customer_id, name, scopes, expires_days).await {
+                    match create_api_key(&pool, &auth_service, options).await {
                         Ok((key, full_key)) => {
                             println!("{}", "✅ API Key created successfully!".green().bold());
                             println!();
@@ -804,8 +1321,15 @@ async fn main() -> Result<()> {
                             println!("  Key: {}", full_key.bright_cyan());
                             println!();
                             println!("  Prefix:      {}", key.key_prefix);
+                            println!("  Type:        {}", key.key_type.as_str());
                             println!("  Name:        {}", key.name);
                             println!("  Scopes:      {}", key.scopes.join(", "));
+                            if key.key_type == ApiKeyType::Publishable {
+                                println!("  Origins:     {}", key.allowed_origins.join(", "));
+                            }
+                            if !key.allowed_ips.is_empty() {
+                                println!("  Allowed IPs: {}", key.allowed_ips.join(", "));
+                            }
                             println!("  Customer ID: {}", key.customer_id.map(|id: Uuid| id.to_string()).unwrap_or_else(|| "System".to_string()));
                             println!("  Expires:     {}", key.expires_at.map(|d| d.to_string()).unwrap_or_else(|| "Never".to_string()));
                         }
@@ -822,8 +1346,18 @@ async fn main() -> Result<()> {
                             println!("{}", "API Key Details".bold().underline());
                             println!("  ID:           {}", key.id);
                             println!("  Prefix:       {}", key.key_prefix);
+                            println!("  Type:         {}", key.key_type.as_str());
                             println!("  Name:         {}", key.name);
                             println!("  Scopes:       {}", key.scopes.join(", "));
+                            if key.key_type == ApiKeyType::Publishable {
+                                println!("  Origins:      {}", key.allowed_origins.join(", "));
+                            }
+                            if !key.allowed_ips.is_empty() {
+                                println!("  Allowed IPs:  {}", key.allowed_ips.join(", "));
+                            }
+                            if let Some(limit) = key.rate_limit_per_minute {
+                                println!("  Rate Limit:   {}/min", limit);
+                            }
                             println!("  Active:       {}", if key.is_active { "✓ Yes".green() } else { "✗ No".red() });
                             println!("  Customer ID:  {}", key.customer_id.map(|id: Uuid| id.to_string()).unwrap_or_else(|| "System".to_string()));
                             println!("  Created:      {}", key.created_at);
@@ -836,6 +1370,9 @@ async fn main() -> Result<()> {
                             if let Some(revoked_at) = key.revoked_at {
                                 println!("  Revoked:      {} {}", revoked_at, key.revoked_reason.unwrap_or_default().red());
                             }
+                            if let Some(rotated_to) = key.rotated_to {
+                                println!("  Rotated To:   {}", rotated_to);
+                            }
                             println!("  Key Hash:     {}...", &key.key_hash[..16]);
                         }
                         Ok(None) => {
@@ -863,6 +1400,32 @@ async fn main() -> Result<()> {
                     }
                 }
                 
+                ApiKeyCommands::Rotate { prefix, grace_hours } => {
+                    let auth_service = rcommerce_core::services::AuthService::new(config);
+                    
+                    match rotate_api_key(&pool, &auth_service, &prefix, grace_hours).await {
+                        Ok(Some((key, full_key))) => {
+                            println!("{}", "✅ API Key rotated successfully!".green().bold());
+                            println!();
+                            println!("{}", "IMPORTANT: Copy this key now - it won't be shown again!".red().bold());
+                            println!();
+                            println!("  Key: {}", full_key.bright_cyan());
+                            println!();
+                            println!("  Prefix:      {}", key.key_prefix);
+                            println!("  Type:        {}", key.key_type.as_str());
+                            println!("  Name:        {}", key.name);
+                            println!("  Old key '{}' stops working in {} hour(s)", prefix, grace_hours);
+                        }
+                        Ok(None) => {
+                            println!("{}", format!("No active API key with prefix '{}'", prefix).yellow());
+                        }
+                        Err(e) => {
+                            eprintln!("{}", format!("❌ Failed to rotate API key: {}", e).red());
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                
                 ApiKeyCommands::Delete { prefix, force } => {
                     if !force {
                         println!("{}", "⚠️  WARNING: This will PERMANENTLY delete the API key!".red().bold());
@@ -898,12 +1461,13 @@ async fn main() -> Result<()> {
         Commands::Import { command } => {
             use colored::*;
             use rcommerce_core::import::{
-                get_file_importer, get_platform_importer, ImportConfig as ImportToolConfig,
+                get_file_importer, get_platform_importer, parse_since, EntityType, ImportConfig as ImportToolConfig,
+                SyncCursors,
             };
             use rcommerce_core::import::types::{ImportOptions, SourceConfig};
             
             match command {
-                ImportCommands::Platform { platform, api_url, api_key, api_secret, entities, limit, dry_run, overwrite, currency } => {
+                ImportCommands::Platform { platform, api_url, api_key, api_secret, entities, limit, dry_run, overwrite, currency, since, incremental } => {
                     println!("{} {}", "Importing from".bold(), platform.cyan());
                     
                     // Get the platform importer
@@ -958,6 +1522,16 @@ async fn main() -> Result<()> {
                         std::process::exit(1);
                     }
                     
+                    let since = match since.as_deref().map(|value| (value, parse_since(value))) {
+                        Some((_, Some(since))) => Some(since),
+                        Some((value, None)) => {
+                            eprintln!("{}", format!("❌ Invalid --since time: {} (use RFC 3339 or YYYY-MM-DD)", value).red());
+                            std::process::exit(1);
+                        }
+                        None => None,
+                    };
+                    let source = final_api_url.clone();
+                    
                     // Build headers for WooCommerce
                     let mut headers = std::collections::HashMap::new();
                     if let Some(ref secret) = final_api_secret {
@@ -989,6 +1563,7 @@ async fn main() -> Result<()> {
                             default_currency: currency,
                             ..Default::default()
                         },
+                        media: Some(config.media.clone()),
                     };
                     
                     // Progress callback
@@ -1003,17 +1578,16 @@ async fn main() -> Result<()> {
                         std::io::Write::flush(&mut std::io::stdout()).unwrap();
                     };
                     
-                    // Parse and validate entity types
-                    let entity_list: Vec<&str> = if final_entities == "all" {
-                        vec!["all"]
-                    } else {
-                        final_entities.split(',').map(|s| s.trim()).collect()
-                    };
+                    // Parse and validate entity types; each has its own sync cursor
+                    let mut entity_list: Vec<&str> = final_entities.split(',').map(|s| s.trim()).collect();
+                    if entity_list.contains(&"all") {
+                        entity_list = vec!["products", "customers", "orders"];
+                    }
                     
                     // Validate all entity types first
                     for entity in &entity_list {
                         match *entity {
-                            "products" | "customers" | "orders" | "all" => {},
+                            "products" | "customers" | "orders" => {},
                             _ => {
                                 eprintln!("{}", format!("❌ Invalid entity type: {}", entity).red());
                                 std::process::exit(1);
@@ -1021,27 +1595,79 @@ async fn main() -> Result<()> {
                         }
                     }
                     
+                    // Sync cursors record when each entity last imported cleanly
+                    let cursors = if dry_run {
+                        None
+                    } else {
+                        match SyncCursors::connect(&import_config.database_url).await {
+                            Ok(cursors) => Some(cursors),
+                            Err(e) if incremental => {
+                                eprintln!("{}", format!("❌ Failed to read sync cursors: {}", e).red());
+                                std::process::exit(1);
+                            }
+                            Err(e) => {
+                                eprintln!("{}", format!("⚠️  Sync cursors unavailable: {}", e).yellow());
+                                None
+                            }
+                        }
+                    };
+                    
                     // Run imports for each entity type
                     let mut all_stats = rcommerce_core::import::ImportStats::default();
                     let mut has_error = false;
                     
                     for entity in entity_list {
-                        if entity == "all" {
-                            println!("\n  {}", "Importing all entities...".bold());
-                        } else {
-                            println!("\n  {} {}", "Importing".bold(), entity.cyan());
+                        let entity_type = match entity {
+                            "products" => EntityType::Products,
+                            "customers" => EntityType::Customers,
+                            "orders" => EntityType::Orders,
+                            _ => unreachable!(),
+                        };
+                        
+                        // --since wins over the last sync's cursor
+                        let mut entity_since = since;
+                        if entity_since.is_none() && incremental {
+                            if let Some(cursors) = &cursors {
+                                match cursors.get(&platform, &source, entity_type).await {
+                                    Ok(cursor) => entity_since = cursor,
+                                    Err(e) => {
+                                        eprintln!("{}", format!("❌ Failed to read the {} sync cursor: {}", entity, e).red());
+                                        std::process::exit(1);
+                                    }
+                                }
+                            }
+                        }
+                        let mut entity_config = import_config.clone();
+                        match entity_since {
+                            Some(changed_since) => {
+                                entity_config.options = entity_config.options.changed_since(changed_since);
+                                println!(
+                                    "\n  {} {} {}",
+                                    "Syncing".bold(),
+                                    entity.cyan(),
+                                    format!("changed since {}", changed_since.to_rfc3339()).dimmed()
+                                );
+                            }
+                            None => println!("\n  {} {}", "Importing".bold(), entity.cyan()),
                         }
                         
-                        let result = match entity {
-                            "products" => importer.import_products(&import_config, &progress).await,
-                            "customers" => importer.import_customers(&import_config, &progress).await,
-                            "orders" => importer.import_orders(&import_config, &progress).await,
-                            "all" => importer.import_all(&import_config, &progress).await,
-                            _ => unreachable!(),
+                        let started_at = chrono::Utc::now();
+                        let result = match entity_type {
+                            EntityType::Products => importer.import_products(&entity_config, &progress).await,
+                            EntityType::Customers => importer.import_customers(&entity_config, &progress).await,
+                            EntityType::Orders => importer.import_orders(&entity_config, &progress).await,
                         };
                         
                         match result {
                             Ok(stats) => {
+                                // Records that failed are fetched again by the next sync
+                                if stats.errors == 0 {
+                                    if let Some(cursors) = &cursors {
+                                        if let Err(e) = cursors.save(&platform, &source, entity_type, started_at).await {
+                                            eprintln!("\n{}", format!("⚠️  Failed to save the {} sync cursor: {}", entity, e).yellow());
+                                        }
+                                    }
+                                }
                                 all_stats.created += stats.created;
                                 all_stats.updated += stats.updated;
                                 all_stats.skipped += stats.skipped;
@@ -1135,6 +1761,7 @@ async fn main() -> Result<()> {
                             continue_on_error: config.import.default_options.continue_on_error,
                             ..Default::default()
                         },
+                        media: Some(config.media.clone()),
                     };
                     
                     // Progress callback
@@ -1401,13 +2028,26 @@ async fn main() -> Result<()> {
             }
         }
         
-        Commands::Config => {
+        Commands::Config { command: None } => {
             println!("Configuration loaded from: {}", 
                 cli.config.map(|p| p.display().to_string()).unwrap_or_else(|| "environment".to_string())
             );
             println!("{:#?}", config);
         }
         
+        Commands::Config { command: Some(command) } => {
+            let result = match command {
+                ConfigCommands::Export { output } => commands::config_bundle::export(&config, output.as_deref()).await,
+                ConfigCommands::Import { file, include_deletes, dry_run, yes } => {
+                    commands::config_bundle::import(&config, &file, include_deletes, dry_run, yes).await
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("{}", format!("❌ Config command failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
         Commands::Email { command } => {
             use colored::*;
             
@@ -1416,34 +2056,65 @@ async fn main() -> Result<()> {
                     println!("{}", "Available Email Templates".bold().underline());
                     println!();
                     
-                    let templates = vec![
-                        ("order_confirmation", "Order Confirmation", "Sent when a new order is placed"),
-                        ("order_shipped", "Order Shipped", "Sent when an order is shipped"),
-                        ("order_cancelled", "Order Cancelled", "Sent when an order is cancelled"),
-                        ("payment_successful", "Payment Successful", "Sent when payment is confirmed"),
-                        ("payment_failed", "Payment Failed", "Sent when payment fails"),
-                        ("refund_processed", "Refund Processed", "Sent when a refund is processed"),
-                        ("subscription_created", "Subscription Created", "Sent when a subscription is created"),
-                        ("subscription_renewal", "Subscription Renewal", "Sent when a subscription renews"),
-                        ("subscription_cancelled", "Subscription Cancelled", "Sent when a subscription is cancelled"),
-                        ("dunning_first", "Dunning: First Notice", "First payment failure notice"),
-                        ("dunning_retry", "Dunning: Retry Notice", "Subsequent payment retry notice"),
-                        ("dunning_final", "Dunning: Final Notice", "Final notice before cancellation"),
-                        ("welcome", "Welcome", "Sent to new customers"),
-                        ("password_reset", "Password Reset", "Sent for password reset requests"),
-                        ("abandoned_cart", "Abandoned Cart", "Sent for abandoned cart reminders"),
-                    ];
+                    let templates = rcommerce_core::notification::catalog::TEMPLATE_TYPES;
                     
                     println!("{:<25} {:<30} Description", "Template ID", "Name");
                     println!("{}", "-".repeat(100));
-                    for (id, name, desc) in &templates {
-                        println!("{:<25} {:<30} {}", id.cyan(), name, desc.dimmed());
+                    for template in templates {
+                        println!("{:<25} {:<30} {}", template.id.cyan(), template.name, template.description.dimmed());
                     }
                     println!();
                     println!("Total: {} templates", templates.len());
                 }
                 
+                EmailCommands::Variables { template, json } => {
+                    use rcommerce_core::notification::catalog;
+                    
+                    let entries = match template {
+                        Some(ref template) => match catalog::catalog_entry(template) {
+                            Some(entry) => vec![entry],
+                            None => {
+                                eprintln!("{}", format!("❌ Unknown template: {}", template).red());
+                                std::process::exit(1);
+                            }
+                        },
+                        None => catalog::catalog(),
+                    };
+                    
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&entries)?);
+                    } else {
+                        for entry in &entries {
+                            println!("{} ({})", entry.template_type.name.bold().underline(), entry.template_type.id.cyan());
+                            for variable in &entry.variables {
+                                println!(
+                                    "  {:<28} {:<40} {}",
+                                    format!("{{{{ {} }}}}", variable.name).cyan(),
+                                    variable.description.unwrap_or_default(),
+                                    truncate_sample(&variable.sample).dimmed()
+                                );
+                            }
+                            println!();
+                        }
+                    }
+                }
+                
+                EmailCommands::Validate { dir, skip_db, json } => {
+                    if !json {
+                        println!("{}", "Validating Email Templates".bold().underline());
+                    }
+                    match commands::email_theme::validate(&config, dir, skip_db, json).await {
+                        Ok(0) => {}
+                        Ok(_) => std::process::exit(1),
+                        Err(e) => {
+                            eprintln!("{}", format!("❌ Email template validation failed: {}", e).red());
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                
                 EmailCommands::TestAll { output_dir, recipient } => {
+                    install_email_theme(&config);
                     let recipient = recipient.unwrap_or_else(|| "test@example.com".to_string());
                     println!("{}", "Testing All Email Templates".bold().underline());
                     println!("  Output directory: {}", output_dir.cyan());
@@ -1463,6 +2134,7 @@ async fn main() -> Result<()> {
                 }
                 
                 EmailCommands::Test { template, output_dir, recipient } => {
+                    install_email_theme(&config);
                     let recipient = recipient.unwrap_or_else(|| "test@example.com".to_string());
                     println!("{}", format!("Testing Email Template: {}", template).bold().underline());
                     println!("  Output directory: {}", output_dir.cyan());
@@ -1514,19 +2186,226 @@ async fn main() -> Result<()> {
             }
         }
         
-        Commands::Shell => {
-            if let Err(e) = security::check_not_root() {
-                eprintln!("{}", e);
-                std::process::exit(1);
-            }
-            
-            let pool = create_pool(&config).await?;
-            
-            if let Err(e) = commands::shell::run_shell(pool).await {
-                eprintln!("{}", format!("❌ Shell error: {}", e).red().bold());
-                std::process::exit(1);
+        Commands::Shell => {
+            if let Err(e) = security::check_not_root() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            
+            let pool = create_pool(&config).await?;
+            
+            if let Err(e) = commands::shell::run_shell(pool).await {
+                eprintln!("{}", format!("❌ Shell error: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Webhook { command } => {
+            match command {
+                WebhookCommands::Replay { webhook_id, from, to, events, failed_only, rate, dry_run } => {
+                    let request = rcommerce_core::models::CreateWebhookReplayRequest {
+                        event_types: events,
+                        from,
+                        to,
+                        failed_only,
+                        rate_per_second: rate,
+                        dry_run,
+                    };
+                    match commands::webhook::run_webhook_replay(&config, webhook_id, request).await {
+                        Ok(0) => {}
+                        Ok(_) => std::process::exit(1),
+                        Err(e) => {
+                            eprintln!("{}", format!("❌ Webhook replay failed: {}", e).red().bold());
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+        
+        Commands::Secrets { command } => {
+            let result = match command {
+                SecretsCommands::GenerateKey => commands::secrets::generate_key(),
+                SecretsCommands::Rotate { dry_run } => match commands::secrets::rotate(&config, dry_run).await {
+                    Ok(0) => Ok(()),
+                    Ok(_) => std::process::exit(1),
+                    Err(e) => Err(e),
+                },
+                SecretsCommands::Status => commands::secrets::status(&config).await,
+            };
+            if let Err(e) = result {
+                eprintln!("{}", format!("❌ Secrets command failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Jobs { command } => {
+            let result = match command {
+                JobsCommands::List { json } => commands::jobs::list(&config, json).await,
+                JobsCommands::Run { name } => commands::jobs::run(&config, &name).await,
+                JobsCommands::Pause { name } => commands::jobs::pause(&config, &name).await,
+                JobsCommands::Resume { name } => commands::jobs::resume(&config, &name).await,
+                JobsCommands::Schedule { name, expression } => {
+                    commands::jobs::schedule(&config, &name, &expression).await
+                }
+                JobsCommands::Failed { all, json } => commands::jobs::failed(&config, all, json).await,
+                JobsCommands::Retry { id } => commands::jobs::retry(&config, id).await,
+                JobsCommands::Discard { id } => commands::jobs::discard(&config, id).await,
+            };
+            if let Err(e) = result {
+                eprintln!("{}", format!("❌ Jobs command failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Report { command } => {
+            let result = match command {
+                ReportCommands::Summary { from, to, days, granularity, top, refresh, json } => {
+                    let options = commands::report::SummaryOptions { from, to, days, granularity, top, refresh, json };
+                    commands::report::summary(&config, options).await
+                }
+                ReportCommands::Refresh => commands::report::refresh(&config).await,
+            };
+            if let Err(e) = result {
+                eprintln!("{}", format!("❌ Report failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Shipping { command } => {
+            let result = match command {
+                ShippingCommands::PrintBatch { shipments, unprinted, location, limit, documents, profile, output, print } => {
+                    let request = rcommerce_core::models::CreatePrintBatchRequest {
+                        shipment_ids: shipments,
+                        unprinted,
+                        location,
+                        limit,
+                        document_kind: Some(documents),
+                        profile,
+                    };
+                    commands::shipping::print_batch(&config, request, output, print).await
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("{}", format!("❌ Print batch failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Replay { file, target, token, path_prefix, include_writes, delay_ms, dry_run } => {
+            let options = commands::replay::ReplayOptions {
+                target,
+                token,
+                path_prefix,
+                include_writes,
+                delay_ms,
+                dry_run,
+            };
+            match commands::replay::run_replay(&file, options).await {
+                Ok(0) => {}
+                Ok(_) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{}", format!("❌ Replay failed: {}", e).red().bold());
+                    std::process::exit(1);
+                }
+            }
+        }
+        
+        Commands::Promote { source, source_token, target, target_token, entities, include_deletes, dry_run, yes } => {
+            let options = commands::promote::PromoteOptions {
+                source,
+                source_token,
+                target,
+                target_token,
+                entities,
+                include_deletes,
+                dry_run,
+                yes,
+            };
+            if let Err(e) = commands::promote::run_promote(options).await {
+                eprintln!("{}", format!("❌ Promotion failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Demo { command } => {
+            match command {
+                DemoCommands::Up { api_port, frontend_port, frontend_bin, frontend_dir, skip_seed, no_frontend } => {
+                    let options = commands::demo::DemoOptions {
+                        api_port,
+                        frontend_port,
+                        frontend_bin,
+                        frontend_dir,
+                        skip_seed,
+                        no_frontend,
+                    };
+                    if let Err(e) = commands::demo::up(config, options).await {
+                        eprintln!("{}", format!("❌ Demo failed: {}", e).red().bold());
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        
+        Commands::Export { entity, format, output, since, until, status } => {
+            let filter = rcommerce_core::models::ExportFilter { since, until, status };
+            if let Err(e) = commands::export::run_export(&config, entity, format, filter, output.as_deref()).await {
+                eprintln!("{}", format!("❌ Export failed: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        
+        Commands::Openapi { output } => {
+            let document = rcommerce_api::openapi::document_json();
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, document) {
+                        eprintln!("{}", format!("❌ Failed to write {}: {}", path.display(), e).red().bold());
+                        std::process::exit(1);
+                    }
+                    eprintln!("{}", format!("✅ OpenAPI document written to {}", path.display()).green().bold());
+                }
+                None => println!("{}", document),
+            }
+        }
+        
+        Commands::EventSchema { output } => {
+            let catalog = rcommerce_api::event_schema::catalog_json();
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, catalog) {
+                        eprintln!("{}", format!("❌ Failed to write {}: {}", path.display(), e).red().bold());
+                        std::process::exit(1);
+                    }
+                    eprintln!("{}", format!("✅ Event schema catalog written to {}", path.display()).green().bold());
+                }
+                None => println!("{}", catalog),
+            }
+        }
+        
+        Commands::Doctor { json } => {
+            match commands::doctor::run_doctor(&config, json).await {
+                Ok(report) if report.has_errors() => std::process::exit(1),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("{}", format!("❌ Doctor failed: {}", e).red().bold());
+                    std::process::exit(1);
+                }
+            }
+        }
+        
+        Commands::Validate { command } => match command {
+            ValidateCommands::Data { fix, checks, json } => {
+                match commands::validate::run_validate(&config, &checks, fix, json).await {
+                    Ok(report) if report.has_errors() => std::process::exit(1),
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("{}", format!("❌ Validation failed: {}", e).red().bold());
+                        std::process::exit(1);
+                    }
+                }
             }
-        }
+        },
     }
     
     Ok(())
@@ -1564,6 +2443,7 @@ async fn run_migrations(config: &Config) -> Result<()> {
 // API Key management functions
 
 use chrono::{DateTime, Utc};
+use rcommerce_core::repository::ApiKeyType;
 use uuid::Uuid;
 
 /// API Key record from database
@@ -1583,6 +2463,23 @@ struct ApiKeyRecord {
     updated_at: DateTime<Utc>,
     revoked_at: Option<DateTime<Utc>>,
     revoked_reason: Option<String>,
+    rate_limit_per_minute: Option<i32>,
+    key_type: ApiKeyType,
+    allowed_origins: Vec<String>,
+    rotated_to: Option<Uuid>,
+    allowed_ips: Vec<String>,
+}
+
+/// Options for `api-key create`
+struct NewApiKey {
+    customer_id: Option<String>,
+    name: Option<String>,
+    scopes: String,
+    expires_days: Option<i64>,
+    publishable: bool,
+    origins: Vec<String>,
+    rate_limit: Option<i32>,
+    allow_ips: Vec<String>,
 }
 
 /// List API keys
@@ -1610,48 +2507,110 @@ async fn list_api_keys(pool: &sqlx::PgPool, customer_id: Option<String>) -> Resu
 async fn create_api_key(
     pool: &sqlx::PgPool,
     auth_service: &rcommerce_core::services::AuthService,
-    customer_id: Option<String>,
-    name: Option<String>,
-    scopes: String,
-    expires_days: Option<i64>,
+    options: NewApiKey,
 ) -> Result<(ApiKeyRecord, String)> {
+    let origins: Vec<String> = options.origins.iter()
+        .map(|o| o.trim().to_string())
+        .filter(|o| !o.is_empty())
+        .collect();
+    if options.publishable && origins.is_empty() {
+        return Err(rcommerce_core::Error::validation("Publishable keys need at least one --origins entry"));
+    }
+    if !options.publishable && !origins.is_empty() {
+        return Err(rcommerce_core::Error::validation("--origins only applies to publishable keys"));
+    }
+    if options.rate_limit.is_some_and(|limit| limit <= 0) {
+        return Err(rcommerce_core::Error::validation("--rate-limit must be positive"));
+    }
+    let allowed_ips: Vec<String> = options.allow_ips.iter()
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+        .collect();
+    if let Some(invalid) = allowed_ips.iter().find(|ip| !rcommerce_core::repository::is_valid_ip_network(ip)) {
+        return Err(rcommerce_core::Error::validation(format!("Invalid --allow-ips entry '{}'", invalid)));
+    }
+    
     // Generate API key
-    let api_key = auth_service.generate_api_key();
+    let (api_key, key_type) = if options.publishable {
+        (auth_service.generate_publishable_key(), ApiKeyType::Publishable)
+    } else {
+        (auth_service.generate_api_key(), ApiKeyType::Secret)
+    };
     let full_key = api_key.full_key.clone().unwrap();
     
     // Parse customer ID if provided
-    let customer_uuid = if let Some(cid) = customer_id {
+    let customer_uuid = if let Some(cid) = options.customer_id {
         Some(Uuid::parse_str(&cid).map_err(|e| rcommerce_core::Error::validation(format!("Invalid customer ID: {}", e)))?)
     } else {
         None
     };
     
     // Calculate expiration
-    let expires_at = expires_days.map(|days| Utc::now() + chrono::Duration::days(days));
+    let expires_at = options.expires_days.map(|days| Utc::now() + chrono::Duration::days(days));
     
-    // Parse scopes
-    let scopes_vec: Vec<String> = scopes.split(',').map(|s| s.trim().to_string()).collect();
+    // Parse scopes; publishable keys can only read the catalog
+    let scopes_vec: Vec<String> = if options.publishable {
+        vec!["products:read".to_string()]
+    } else {
+        options.scopes.split(',').map(|s| s.trim().to_string()).collect()
+    };
     
     // Insert into database
     let key = sqlx::query_as::<_, ApiKeyRecord>(
         r#"
-        INSERT INTO api_keys (customer_id, key_prefix, key_hash, name, scopes, expires_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO api_keys (
+            customer_id, key_prefix, key_hash, name, scopes, expires_at,
+            rate_limit_per_minute, key_type, allowed_origins, allowed_ips
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         RETURNING *
         "#
     )
     .bind(customer_uuid)
     .bind(&api_key.prefix)
     .bind(&api_key.hash)
-    .bind(name.unwrap_or_else(|| "API Key".to_string()))
+    .bind(options.name.unwrap_or_else(|| "API Key".to_string()))
     .bind(&scopes_vec)
     .bind(expires_at)
+    .bind(options.rate_limit)
+    .bind(key_type)
+    .bind(&origins)
+    .bind(&allowed_ips)
     .fetch_one(pool)
     .await?;
     
     Ok((key, full_key))
 }
 
+/// Rotate an API key, keeping the old one valid for `grace_hours`
+async fn rotate_api_key(
+    pool: &sqlx::PgPool,
+    auth_service: &rcommerce_core::services::AuthService,
+    prefix: &str,
+    grace_hours: i64,
+) -> Result<Option<(rcommerce_core::repository::ApiKeyRecord, String)>> {
+    use rcommerce_core::repository::{ApiKeyRepository, PostgresApiKeyRepository};
+    
+    if grace_hours < 0 {
+        return Err(rcommerce_core::Error::validation("--grace-hours cannot be negative"));
+    }
+    let Some(old) = get_api_key(pool, prefix).await? else {
+        return Ok(None);
+    };
+    
+    let api_key = match old.key_type {
+        ApiKeyType::Publishable => auth_service.generate_publishable_key(),
+        ApiKeyType::Secret => auth_service.generate_api_key(),
+    };
+    let full_key = api_key.full_key.clone().unwrap();
+    
+    let repo = PostgresApiKeyRepository::new(pool.clone());
+    let key = repo
+        .rotate(prefix, &api_key.prefix, &api_key.hash, chrono::Duration::hours(grace_hours))
+        .await?;
+    Ok(key.map(|key| (key, full_key)))
+}
+
 /// Get API key by prefix
 async fn get_api_key(pool: &sqlx::PgPool, prefix: &str) -> Result<Option<ApiKeyRecord>> {
     let key = sqlx::query_as::<_, ApiKeyRecord>(
@@ -1712,7 +2671,7 @@ struct ProductRecord {
 async fn list_products(pool: &sqlx::PgPool) -> Result<Vec<ProductRecord>> {
     let products = sqlx::query_as::<_, ProductRecord>(
         "SELECT id, title, slug, price, currency::text, description, is_active, inventory_quantity, created_at 
-         FROM products ORDER BY created_at DESC"
+         FROM products WHERE deleted_at IS NULL ORDER BY created_at DESC"
     )
     .fetch_all(pool)
     .await?;
@@ -1727,7 +2686,7 @@ async fn get_product(pool: &sqlx::PgPool, id: &str) -> Result<Option<ProductReco
     
     let product = sqlx::query_as::<_, ProductRecord>(
         "SELECT id, title, slug, price, currency::text, description, is_active, inventory_quantity, created_at 
-         FROM products WHERE id = $1"
+         FROM products WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(product_id)
     .fetch_optional(pool)
@@ -1736,17 +2695,36 @@ async fn get_product(pool: &sqlx::PgPool, id: &str) -> Result<Option<ProductReco
     Ok(product)
 }
 
-/// Delete product by ID
-async fn delete_product(pool: &sqlx::PgPool, id: &str) -> Result<bool> {
-    let product_id = Uuid::parse_str(id)
-        .map_err(|e| rcommerce_core::Error::validation(format!("Invalid product ID: {}", e)))?;
+/// Soft-delete or restore a product, customer or order; exits on failure
+async fn soft_delete_record(pool: &sqlx::PgPool, config: &Config, kind: SoftDeleteKind, id: &str, restore: bool) {
+    use rcommerce_core::repository::PostgresSoftDeleteRepository;
+    use rcommerce_core::services::SoftDeleteService;
     
-    let result = sqlx::query("DELETE FROM products WHERE id = $1")
-        .bind(product_id)
-        .execute(pool)
-        .await?;
-    
-    Ok(result.rows_affected() > 0)
+    let soft_deletes = SoftDeleteService::new(
+        PostgresSoftDeleteRepository::new(pool.clone()),
+        config.soft_delete.clone(),
+    );
+    let result = match Uuid::parse_str(id) {
+        Ok(record_id) if restore => soft_deletes.restore(kind, record_id).await,
+        Ok(record_id) => soft_deletes.delete(kind, record_id).await,
+        Err(e) => Err(rcommerce_core::Error::validation(format!("Invalid {} ID: {}", kind.noun().to_lowercase(), e))),
+    };
+    match result {
+        Ok(()) if restore => println!("{}", format!("✅ {} '{}' restored", kind.noun(), id).green()),
+        Ok(()) => {
+            println!("{}", format!("✅ {} '{}' deleted", kind.noun(), id).green());
+            if config.soft_delete.retention_days > 0 {
+                println!("   Restorable for {} days with `rcommerce {} restore {}`",
+                    config.soft_delete.retention_days, kind.noun().to_lowercase(), id);
+            }
+        }
+        Err(rcommerce_core::Error::NotFound(msg)) => println!("{}", msg.yellow()),
+        Err(e) => {
+            let action = if restore { "restore" } else { "delete" };
+            eprintln!("{}", format!("❌ Failed to {} {}: {}", action, kind.noun().to_lowercase(), e).red());
+            std::process::exit(1);
+        }
+    }
 }
 
 // Order CLI functions
@@ -1754,20 +2732,55 @@ async fn delete_product(pool: &sqlx::PgPool, id: &str) -> Result<bool> {
 #[derive(Debug, sqlx::FromRow)]
 struct OrderRecord {
     id: Uuid,
+    order_number: String,
     customer_email: String,
     status: String,
     total: rust_decimal::Decimal,
     created_at: chrono::DateTime<chrono::Utc>,
 }
 
-/// List all orders
-async fn list_orders(pool: &sqlx::PgPool) -> Result<Vec<OrderRecord>> {
+/// Print all orders, or those whose number contains `number`
+async fn print_orders(pool: &sqlx::PgPool, number: Option<&str>) {
+    match list_orders(pool, number).await {
+        Ok(orders) => {
+            if orders.is_empty() {
+                println!("{}", "No orders found".yellow());
+            } else {
+                println!("{}", "Orders".bold().underline());
+                println!("{:<36} {:<20} {:<20} {:<12} {:<15} {:<12}", 
+                    "ID", "Number", "Customer", "Status", "Total", "Created");
+                println!("{}", "-".repeat(121));
+                for o in &orders {
+                    println!("{:<36} {:<20} {:<20} {:<12} {:<15.2} {:<12}",
+                        o.id.to_string(),
+                        truncate(&o.order_number, 20),
+                        truncate(&o.customer_email, 18),
+                        o.status,
+                        o.total,
+                        o.created_at.format("%Y-%m-%d")
+                    );
+                }
+                println!("\nTotal: {} orders", orders.len());
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", format!("❌ Failed to list orders: {}", e).red());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// List all orders, or those whose number contains `number`
+async fn list_orders(pool: &sqlx::PgPool, number: Option<&str>) -> Result<Vec<OrderRecord>> {
     let orders = sqlx::query_as::<_, OrderRecord>(
-        "SELECT o.id, c.email as customer_email, o.status::text, o.total, o.created_at 
+        "SELECT o.id, o.order_number, c.email as customer_email, o.status::text, o.total, o.created_at 
          FROM orders o 
          JOIN customers c ON o.customer_id = c.id 
+         WHERE o.deleted_at IS NULL
+           AND ($1::TEXT IS NULL OR o.order_number ILIKE '%' || $1 || '%')
          ORDER BY o.created_at DESC"
     )
+    .bind(number)
     .fetch_all(pool)
     .await?;
     
@@ -1789,7 +2802,7 @@ struct CustomerRecord {
 async fn list_customers(pool: &sqlx::PgPool) -> Result<Vec<CustomerRecord>> {
     let customers = sqlx::query_as::<_, CustomerRecord>(
         "SELECT id, email, first_name, last_name, created_at 
-         FROM customers ORDER BY created_at DESC"
+         FROM customers WHERE deleted_at IS NULL ORDER BY created_at DESC"
     )
     .fetch_all(pool)
     .await?;
@@ -2309,289 +3322,37 @@ async fn obtain_certificate_stub(le_config: &LetsEncryptConfig, domain: &str) ->
 
 // Email testing functions
 
-use rcommerce_core::notification::{EmailNotificationFactory, Notification, NotificationTemplate, TemplateVariables};
-use rcommerce_core::notification::email_templates::{OrderItem, Address, OrderConfirmationParams};
+use rcommerce_core::notification::{catalog, Notification, NotificationTemplate, TemplateVariables};
 use std::fs::File;
 use std::io::Write;
 
-/// Generate a test order confirmation email
-fn generate_order_confirmation_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let items = vec![
-        OrderItem {
-            name: "High-Performance Server Blade".to_string(),
-            sku: "SRV-BLD-01".to_string(),
-            quantity: 2,
-            price: "3,200.00".to_string(),
-        },
-        OrderItem {
-            name: "Enterprise Rust Support".to_string(),
-            sku: "LIC-ENT-YR".to_string(),
-            quantity: 1,
-            price: "850.00".to_string(),
-        },
-    ];
-    
-    let shipping = Address {
-        name: "John Doe".to_string(),
-        street: "123 Main Street, Suite 100".to_string(),
-        city: "San Francisco".to_string(),
-        state: "CA".to_string(),
-        zip: "94102".to_string(),
-        country: "United States".to_string(),
-    };
-    
-    let billing = Address {
-        name: "John Doe".to_string(),
-        street: "456 Business Ave".to_string(),
-        city: "New York".to_string(),
-        state: "NY".to_string(),
-        zip: "10001".to_string(),
-        country: "United States".to_string(),
-    };
-    
-    EmailNotificationFactory::order_confirmation(OrderConfirmationParams {
-        recipient_email: recipient,
-        customer_name: "John Doe",
-        order_number: "ORD-2026-001234",
-        order_date: "Feb 5, 2026",
-        order_total: "7,250.00",
-        items: &items,
-        shipping_address: &shipping,
-        billing_address: &billing,
-    })
-}
-
-/// Generate a test order shipped email
-fn generate_order_shipped_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("order_shipped_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("order_date", "Feb 5, 2026");
-    vars.insert("tracking_number", "1Z999AA10123456784");
-    vars.insert("tracking_url", "https://tracking.example.com/1Z999AA10123456784");
-    vars.insert("shipping_carrier", "UPS");
-    vars.insert("estimated_delivery", "Feb 8, 2026");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test payment successful email
-fn generate_payment_successful_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("payment_successful_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("amount", "$7,250.00");
-    vars.insert("payment_method", "Visa ending in 4242");
-    vars.insert("payment_date", "Feb 5, 2026");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test payment failed email
-fn generate_payment_failed_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("payment_failed_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("amount", "$7,250.00");
-    vars.insert("error_message", "Your card was declined. Please try a different payment method.");
-    vars.insert("retry_url", "https://rcommerce.local/payment/retry/ORD-2026-001234");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test subscription created email
-fn generate_subscription_created_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("subscription_created_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("subscription_id", "SUB-2026-001");
-    vars.insert("plan_name", "Enterprise Plan");
-    vars.insert("amount", "$299.00");
-    vars.insert("interval", "Monthly");
-    vars.insert("next_billing_date", "Mar 5, 2026");
-    vars.insert("trial_end_date", "Feb 12, 2026");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test subscription renewal email
-fn generate_subscription_renewal_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("subscription_renewal_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("plan_name", "Enterprise Plan");
-    vars.insert("amount", "$299.00");
-    vars.insert("billing_date", "Feb 5, 2026");
-    vars.insert("next_billing_date", "Mar 5, 2026");
-    vars.insert("invoice_url", "https://rcommerce.local/invoices/INV-2026-001");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test subscription cancelled email
-fn generate_subscription_cancelled_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("subscription_cancelled_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("plan_name", "Enterprise Plan");
-    vars.insert("cancellation_date", "Feb 5, 2026");
-    vars.insert("end_date", "Mar 5, 2026");
-    vars.insert("access_until", "Mar 5, 2026");
-    vars.insert("reason", "Customer requested cancellation");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test dunning first email
-fn generate_dunning_first_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("dunning_first_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("amount", "$299.00");
-    vars.insert("payment_method", "Visa ending in 4242");
-    vars.insert("error_message", "Your card was declined.");
-    vars.insert("retry_url", "https://rcommerce.local/payment/update");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test dunning retry email
-fn generate_dunning_retry_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("dunning_retry_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("amount", "$299.00");
-    vars.insert("attempt_number", "2");
-    vars.insert("max_attempts", "4");
-    vars.insert("next_retry_date", "Feb 7, 2026");
-    vars.insert("update_payment_url", "https://rcommerce.local/payment/update");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test dunning final email
-fn generate_dunning_final_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("dunning_final_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("amount", "$299.00");
-    vars.insert("final_date", "Feb 10, 2026");
-    vars.insert("cancellation_date", "Feb 12, 2026");
-    vars.insert("update_payment_url", "https://rcommerce.local/payment/update");
-    vars.insert("contact_support_url", "https://rcommerce.local/support");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test welcome email
-fn generate_welcome_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("welcome_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("login_url", "https://rcommerce.local/login");
-    vars.insert("shop_url", "https://rcommerce.local/shop");
-    vars.insert("support_email", "support@rcommerce.local");
-    vars.insert("help_center_url", "https://rcommerce.local/help");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test password reset email
-fn generate_password_reset_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("password_reset_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("reset_url", "https://rcommerce.local/reset-password?token=abc123xyz");
-    vars.insert("reset_token", "ABC123XYZ789");
-    vars.insert("expires_in", "24 hours");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
-}
-
-/// Generate a test abandoned cart email
-fn generate_abandoned_cart_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("abandoned_cart_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("cart_items", "High-Performance Server Blade (x2), Enterprise Rust Support (x1)");
-    vars.insert("cart_total", "$7,250.00");
-    vars.insert("cart_url", "https://rcommerce.local/cart");
-    vars.insert("discount_code", "COMEBACK10");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
+/// Generate a test email from the catalog's sample data
+fn generate_test_email(template: &str, recipient: &str) -> rcommerce_core::Result<Notification> {
+    let template_type = catalog::template_type(template)
+        .ok_or_else(|| rcommerce_core::Error::validation(format!("Unknown template: {}", template)))?;
+    let template = NotificationTemplate::load(template_type.template_id)?;
+    let vars = catalog::sample_variables(template_type.id).unwrap_or_default();
     
     create_notification_from_template(recipient, &template, vars)
 }
 
-/// Generate a test order cancelled email
-fn generate_order_cancelled_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("order_cancelled_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("order_date", "Feb 5, 2026");
-    vars.insert("cancellation_reason", "Customer requested cancellation");
-    vars.insert("refund_amount", "$7,250.00");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
+/// Preview emails with the configured branding and template directory;
+/// the built-ins are used if the directory can't be read
+fn install_email_theme(config: &Config) {
+    use colored::*;
+    if let Err(e) = commands::email_theme::install_local(config) {
+        eprintln!("{}", format!("⚠️  Using the built-in templates: {}", e).yellow());
+    }
 }
 
-/// Generate a test refund processed email
-fn generate_refund_processed_email(recipient: &str) -> rcommerce_core::Result<Notification> {
-    let template = NotificationTemplate::load("refund_processed_html")?;
-    
-    let mut vars = TemplateVariables::new();
-    vars.insert("customer_name", "John Doe");
-    vars.insert("order_number", "ORD-2026-001234");
-    vars.insert("refund_amount", "$7,250.00");
-    vars.insert("refund_method", "Original payment method (Visa ending in 4242)");
-    vars.insert("processing_time", "5-7 business days");
-    vars.insert("company_name", "R Commerce");
-    vars.insert("support_email", "support@rcommerce.local");
-    
-    create_notification_from_template(recipient, &template, vars)
+/// Shorten a sample value to one line for table output
+fn truncate_sample(sample: &str) -> String {
+    let line = sample.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > 40 {
+        format!("{}...", line.chars().take(37).collect::<String>())
+    } else {
+        line
+    }
 }
 
 /// Helper function to create a notification from a template
@@ -2633,30 +3394,9 @@ async fn test_all_email_templates(output_dir: &str, recipient: &str) -> rcommerc
     fs::create_dir_all(output_dir)
         .map_err(|e| rcommerce_core::Error::config(format!("Failed to create output directory: {}", e)))?;
     
-    /// Type alias for email generator function
-    type EmailGenerator = Box<dyn Fn(&str) -> rcommerce_core::Result<Notification>>;
-    
-    let generators: Vec<(&str, EmailGenerator)> = vec![
-        ("order_confirmation", Box::new(generate_order_confirmation_email)),
-        ("order_shipped", Box::new(generate_order_shipped_email)),
-        ("order_cancelled", Box::new(generate_order_cancelled_email)),
-        ("payment_successful", Box::new(generate_payment_successful_email)),
-        ("payment_failed", Box::new(generate_payment_failed_email)),
-        ("refund_processed", Box::new(generate_refund_processed_email)),
-        ("subscription_created", Box::new(generate_subscription_created_email)),
-        ("subscription_renewal", Box::new(generate_subscription_renewal_email)),
-        ("subscription_cancelled", Box::new(generate_subscription_cancelled_email)),
-        ("dunning_first", Box::new(generate_dunning_first_email)),
-        ("dunning_retry", Box::new(generate_dunning_retry_email)),
-        ("dunning_final", Box::new(generate_dunning_final_email)),
-        ("welcome", Box::new(generate_welcome_email)),
-        ("password_reset", Box::new(generate_password_reset_email)),
-        ("abandoned_cart", Box::new(generate_abandoned_cart_email)),
-    ];
-    
     let mut count = 0;
-    for (name, generator) in generators {
-        match generator(recipient) {
+    for name in catalog::TEMPLATE_TYPES.iter().map(|t| t.id) {
+        match generate_test_email(name, recipient) {
             Ok(notification) => {
                 let filename = format!("{}_{}.html", name, chrono::Utc::now().format("%Y%m%d_%H%M%S"));
                 let filepath = format!("{}/{}", output_dir, filename);
@@ -2697,24 +3437,7 @@ async fn test_email_template(template: &str, output_dir: &str, recipient: &str)
     fs::create_dir_all(output_dir)
         .map_err(|e| rcommerce_core::Error::config(format!("Failed to create output directory: {}", e)))?;
     
-    let notification = match template {
-        "order_confirmation" => generate_order_confirmation_email(recipient),
-        "order_shipped" => generate_order_shipped_email(recipient),
-        "order_cancelled" => generate_order_cancelled_email(recipient),
-        "payment_successful" => generate_payment_successful_email(recipient),
-        "payment_failed" => generate_payment_failed_email(recipient),
-        "refund_processed" => generate_refund_processed_email(recipient),
-        "subscription_created" => generate_subscription_created_email(recipient),
-        "subscription_renewal" => generate_subscription_renewal_email(recipient),
-        "subscription_cancelled" => generate_subscription_cancelled_email(recipient),
-        "dunning_first" => generate_dunning_first_email(recipient),
-        "dunning_retry" => generate_dunning_retry_email(recipient),
-        "dunning_final" => generate_dunning_final_email(recipient),
-        "welcome" => generate_welcome_email(recipient),
-        "password_reset" => generate_password_reset_email(recipient),
-        "abandoned_cart" => generate_abandoned_cart_email(recipient),
-        _ => return Err(rcommerce_core::Error::validation(format!("Unknown template: {}", template))),
-    }?;
+    let notification = generate_test_email(template, recipient)?;
     
     let filename = format!("{}_{}.html", template, chrono::Utc::now().format("%Y%m%d_%H%M%S"));
     let filepath = format!("{}/{}", output_dir, filename);
@@ -2740,24 +3463,7 @@ async fn test_email_template(template: &str, output_dir: &str, recipient: &str)
 
 /// Send a mock email (outputs to console)
 async fn send_mock_email(template: &str, recipient: &str) -> rcommerce_core::Result<()> {
-    let notification = match template {
-        "order_confirmation" => generate_order_confirmation_email(recipient),
-        "order_shipped" => generate_order_shipped_email(recipient),
-        "order_cancelled" => generate_order_cancelled_email(recipient),
-        "payment_successful" => generate_payment_successful_email(recipient),
-        "payment_failed" => generate_payment_failed_email(recipient),
-        "refund_processed" => generate_refund_processed_email(recipient),
-        "subscription_created" => generate_subscription_created_email(recipient),
-        "subscription_renewal" => generate_subscription_renewal_email(recipient),
-        "subscription_cancelled" => generate_subscription_cancelled_email(recipient),
-        "dunning_first" => generate_dunning_first_email(recipient),
-        "dunning_retry" => generate_dunning_retry_email(recipient),
-        "dunning_final" => generate_dunning_final_email(recipient),
-        "welcome" => generate_welcome_email(recipient),
-        "password_reset" => generate_password_reset_email(recipient),
-        "abandoned_cart" => generate_abandoned_cart_email(recipient),
-        _ => return Err(rcommerce_core::Error::validation(format!("Unknown template: {}", template))),
-    }?;
+    let notification = generate_test_email(template, recipient)?;
     
     // Output to console in mock format
     println!("╔══════════════════════════════════════════════════════════════╗");
@@ -2813,4 +3519,324 @@ mod tests {
         let cli = Cli::parse_from(&["rcommerce", "tls", "info", "--domain", "example.com"]);
         assert!(matches!(cli.command, Commands::Tls { command: TlsCommands::Info { .. } }));
     }
+    
+    #[test]
+    fn test_import_sync_flags_parse() {
+        let cli = Cli::parse_from(["rcommerce", "import", "platform", "shopify", "--incremental"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Import { command: ImportCommands::Platform { incremental: true, since: None, .. } }
+        ));
+        
+        let cli = Cli::parse_from(["rcommerce", "import", "platform", "woocommerce", "--since", "2024-05-01"]);
+        match cli.command {
+            Commands::Import { command: ImportCommands::Platform { since, incremental, .. } } => {
+                assert_eq!(since.as_deref(), Some("2024-05-01"));
+                assert!(!incremental);
+            }
+            _ => panic!("expected import platform"),
+        }
+    }
+    
+    #[test]
+    fn test_doctor_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "doctor"]);
+        assert!(matches!(cli.command, Commands::Doctor { json: false }));
+        
+        let cli = Cli::parse_from(["rcommerce", "doctor", "--json"]);
+        assert!(matches!(cli.command, Commands::Doctor { json: true }));
+    }
+    
+    #[test]
+    fn test_validate_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "validate", "data", "--fix", "--check", "negative_stock", "--check", "order_totals"]);
+        match cli.command {
+            Commands::Validate { command: ValidateCommands::Data { fix, checks, json } } => {
+                assert!(fix);
+                assert!(!json);
+                assert_eq!(checks, vec!["negative_stock", "order_totals"]);
+            }
+            _ => panic!("Expected validate data command"),
+        }
+    }
+    
+    #[test]
+    fn test_replay_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "replay", "capture.json", "--target", "http://staging", "--dry-run"]);
+        assert!(matches!(cli.command, Commands::Replay { dry_run: true, include_writes: false, .. }));
+    }
+    
+    #[test]
+    fn test_promote_command_parse() {
+        let cli = Cli::parse_from([
+            "rcommerce", "promote",
+            "--source", "https://staging.example.com", "--source-token", "a",
+            "--target", "https://shop.example.com", "--target-token", "b",
+            "--entity", "product", "--entity", "tax_rate", "--delete",
+        ]);
+        match cli.command {
+            Commands::Promote { entities, include_deletes, dry_run, yes, .. } => {
+                assert_eq!(
+                    entities,
+                    vec![rcommerce_core::models::CatalogEntity::Product, rcommerce_core::models::CatalogEntity::TaxRate]
+                );
+                assert!(include_deletes);
+                assert!(!dry_run && !yes);
+            }
+            _ => panic!("Expected promote command"),
+        }
+        
+        assert!(Cli::try_parse_from([
+            "rcommerce", "promote", "--source", "s", "--source-token", "a", "--target", "t", "--target-token", "b",
+            "--entity", "coupon",
+        ])
+        .is_err());
+    }
+    
+    #[test]
+    fn test_email_variables_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "email", "variables", "welcome", "--json"]);
+        match cli.command {
+            Commands::Email { command: EmailCommands::Variables { template, json } } => {
+                assert_eq!(template.as_deref(), Some("welcome"));
+                assert!(json);
+            }
+            _ => panic!("Expected email variables command"),
+        }
+    }
+    
+    #[test]
+    fn test_email_validate_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "email", "validate", "--dir", "./emails", "--skip-db"]);
+        match cli.command {
+            Commands::Email { command: EmailCommands::Validate { dir, skip_db, json } } => {
+                assert_eq!(dir, Some(PathBuf::from("./emails")));
+                assert!(skip_db);
+                assert!(!json);
+            }
+            _ => panic!("Expected email validate command"),
+        }
+    }
+    
+    #[test]
+    fn test_order_archive_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "order", "archive", "--dry-run"]);
+        assert!(matches!(cli.command, Commands::Order { command: OrderCommands::Archive { dry_run: true } }));
+    }
+    
+    #[test]
+    fn test_restore_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "customer", "restore", "0b9f3c1e-2a4d-4f6b-8c7d-9e0a1b2c3d4e"]);
+        assert!(matches!(cli.command, Commands::Customer { command: CustomerCommands::Restore { .. } }));
+    }
+    
+    #[test]
+    fn test_report_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "report", "summary", "--from", "2026-10-01", "--granularity", "week", "--refresh"]);
+        match cli.command {
+            Commands::Report { command: ReportCommands::Summary { from, to, days, granularity, refresh, .. } } => {
+                assert_eq!(from, chrono::NaiveDate::from_ymd_opt(2026, 10, 1));
+                assert_eq!(to, None);
+                assert_eq!(days, 30);
+                assert_eq!(granularity, rcommerce_core::reports::Granularity::Week);
+                assert!(refresh);
+            }
+            _ => panic!("Expected report summary command"),
+        }
+    }
+    
+    #[test]
+    fn test_db_partitions_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "db", "partitions", "--months-ahead", "6"]);
+        assert!(matches!(cli.command, Commands::Db { command: DbCommands::Partitions { months_ahead: 6 } }));
+    }
+    
+    #[test]
+    fn test_webhook_replay_command_parse() {
+        let cli = Cli::parse_from([
+            "rcommerce", "webhook", "replay", "5f8c3c1e-6a4b-4b8e-9d2a-1c2b3d4e5f60",
+            "--from", "2026-10-01T00:00:00Z", "--to", "2026-10-02T00:00:00Z",
+            "--event", "order.created", "--event", "order.paid", "--rate", "2",
+        ]);
+        match cli.command {
+            Commands::Webhook { command: WebhookCommands::Replay { events, rate, failed_only, dry_run, .. } } => {
+                assert_eq!(events, vec!["order.created", "order.paid"]);
+                assert_eq!(rate, Some(2));
+                assert!(!failed_only && !dry_run);
+            }
+            _ => panic!("Expected webhook replay command"),
+        }
+    }
+    
+    #[test]
+    fn test_export_command_parse() {
+        use rcommerce_core::models::{ExportEntity, ExportFormat};
+        
+        let cli = Cli::parse_from([
+            "rcommerce", "export", "orders", "--format", "json", "--since", "2026-10-01T00:00:00Z",
+            "--status", "completed", "-o", "orders.json",
+        ]);
+        match cli.command {
+            Commands::Export { entity, format, output, since, until, status } => {
+                assert_eq!(entity, ExportEntity::Orders);
+                assert_eq!(format, ExportFormat::Json);
+                assert_eq!(output, Some(PathBuf::from("orders.json")));
+                assert!(since.is_some() && until.is_none());
+                assert_eq!(status.as_deref(), Some("completed"));
+            }
+            _ => panic!("Expected export command"),
+        }
+        
+        let cli = Cli::parse_from(["rcommerce", "export", "products"]);
+        assert!(matches!(cli.command, Commands::Export { format: ExportFormat::Csv, output: None, .. }));
+        assert!(Cli::try_parse_from(["rcommerce", "export", "invoices"]).is_err());
+    }
+    
+    #[test]
+    fn test_api_key_publishable_command_parse() {
+        let cli = Cli::parse_from([
+            "rcommerce", "api-key", "create", "--publishable",
+            "--origins", "https://shop.example.com,*.example.com", "--rate-limit", "120",
+        ]);
+        match cli.command {
+            Commands::ApiKey { command: ApiKeyCommands::Create { publishable, origins, rate_limit, .. } } => {
+                assert!(publishable);
+                assert_eq!(origins, vec!["https://shop.example.com", "*.example.com"]);
+                assert_eq!(rate_limit, Some(120));
+            }
+            _ => panic!("Expected api-key create command"),
+        }
+        
+        let cli = Cli::parse_from(["rcommerce", "api-key", "rotate", "pk_abc123"]);
+        match cli.command {
+            Commands::ApiKey { command: ApiKeyCommands::Rotate { prefix, grace_hours } } => {
+                assert_eq!(prefix, "pk_abc123");
+                assert_eq!(grace_hours, 24);
+            }
+            _ => panic!("Expected api-key rotate command"),
+        }
+    }
+    
+    #[test]
+    fn test_api_key_allow_ips_command_parse() {
+        let cli = Cli::parse_from([
+            "rcommerce", "api-key", "create", "-s", "orders:read", "--allow-ips", "203.0.113.7,10.0.0.0/8",
+        ]);
+        match cli.command {
+            Commands::ApiKey { command: ApiKeyCommands::Create { allow_ips, publishable, .. } } => {
+                assert!(!publishable);
+                assert_eq!(allow_ips, vec!["203.0.113.7", "10.0.0.0/8"]);
+            }
+            _ => panic!("Expected api-key create command"),
+        }
+    }
+    
+    #[test]
+    fn test_secrets_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "secrets", "rotate", "--dry-run"]);
+        match cli.command {
+            Commands::Secrets { command: SecretsCommands::Rotate { dry_run } } => assert!(dry_run),
+            _ => panic!("Expected secrets rotate command"),
+        }
+        
+        let cli = Cli::parse_from(["rcommerce", "secrets", "generate-key"]);
+        assert!(matches!(cli.command, Commands::Secrets { command: SecretsCommands::GenerateKey }));
+    }
+    
+    #[test]
+    fn test_jobs_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "jobs", "schedule", "subscription_billing", "0 * * * *"]);
+        match cli.command {
+            Commands::Jobs { command: JobsCommands::Schedule { name, expression } } => {
+                assert_eq!(name, "subscription_billing");
+                assert_eq!(expression, "0 * * * *");
+            }
+            _ => panic!("Expected jobs schedule command"),
+        }
+        
+        let cli = Cli::parse_from(["rcommerce", "jobs", "pause", "subscription_billing"]);
+        assert!(matches!(cli.command, Commands::Jobs { command: JobsCommands::Pause { .. } }));
+        
+        let id = uuid::Uuid::new_v4();
+        let cli = Cli::parse_from(["rcommerce", "jobs", "retry", &id.to_string()]);
+        match cli.command {
+            Commands::Jobs { command: JobsCommands::Retry { id: parsed } } => assert_eq!(parsed, id),
+            _ => panic!("Expected jobs retry command"),
+        }
+        assert!(Cli::try_parse_from(["rcommerce", "jobs", "retry", "subscription_billing"]).is_err());
+        
+        let cli = Cli::parse_from(["rcommerce", "jobs", "failed", "--all"]);
+        assert!(matches!(cli.command, Commands::Jobs { command: JobsCommands::Failed { all: true, json: false } }));
+    }
+    
+    #[test]
+    fn test_shipping_print_batch_parse() {
+        let cli = Cli::parse_from(["rcommerce", "shipping", "print-batch", "--unprinted", "--location", "BER", "--print"]);
+        match cli.command {
+            Commands::Shipping { command: ShippingCommands::PrintBatch { shipments, unprinted, location, documents, print, .. } } => {
+                assert!(shipments.is_empty());
+                assert!(unprinted);
+                assert_eq!(location.as_deref(), Some("BER"));
+                assert_eq!(documents, rcommerce_core::models::PrintDocumentKind::ShippingLabel);
+                assert!(print);
+            }
+            _ => panic!("Expected shipping print-batch command"),
+        }
+        
+        let id = uuid::Uuid::new_v4().to_string();
+        let cli = Cli::parse_from(["rcommerce", "shipping", "print-batch", "--shipment", &id, "--documents", "packing_slips"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Shipping { command: ShippingCommands::PrintBatch { documents: rcommerce_core::models::PrintDocumentKind::PackingSlip, .. } }
+        ));
+        
+        assert!(Cli::try_parse_from(["rcommerce", "shipping", "print-batch", "--shipment", &id, "--unprinted"]).is_err());
+    }
+    
+    #[test]
+    fn test_config_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "config"]);
+        assert!(matches!(cli.command, Commands::Config { command: None }));
+        
+        let cli = Cli::parse_from(["rcommerce", "config", "export", "-o", "store.yaml"]);
+        match cli.command {
+            Commands::Config { command: Some(ConfigCommands::Export { output }) } => {
+                assert_eq!(output, Some(PathBuf::from("store.yaml")));
+            }
+            _ => panic!("Expected config export command"),
+        }
+        
+        let cli = Cli::parse_from(["rcommerce", "config", "import", "store.yaml", "--delete", "--dry-run"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config { command: Some(ConfigCommands::Import { include_deletes: true, dry_run: true, yes: false, .. }) }
+        ));
+    }
+    
+    #[test]
+    fn test_openapi_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "openapi", "-o", "openapi.json"]);
+        assert!(matches!(cli.command, Commands::Openapi { output: Some(ref path) } if path == &PathBuf::from("openapi.json")));
+    }
+
+    #[test]
+    fn test_demo_up_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "demo", "up", "--api-port", "9090", "--no-frontend"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Demo { command: DemoCommands::Up { api_port: 9090, frontend_port: 3000, no_frontend: true, .. } }
+        ));
+    }
+
+    #[test]
+    fn test_worker_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "worker", "--concurrency", "8"]);
+        assert!(matches!(cli.command, Commands::Worker { concurrency: Some(8), drain_timeout: None }));
+    }
+
+    #[test]
+    fn test_event_schema_command_parse() {
+        let cli = Cli::parse_from(["rcommerce", "event-schema", "-o", "events.schema.json"]);
+        assert!(matches!(cli.command, Commands::EventSchema { output: Some(ref path) } if path == &PathBuf::from("events.schema.json")));
+    }
 }
@@ -0,0 +1,68 @@
+-- ============================================================================
+-- Migration: Historical Exchange Rates
+-- ============================================================================
+-- Stores every exchange rate the store has used, and snapshots the rate onto
+-- orders and payments at transaction time so analytics, tax and accounting
+-- use the rate that applied when the money moved, not today's rate.
+--
+-- Rates are "1 unit of quote_currency = rate units of base_currency".
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    base_currency currency NOT NULL,
+    quote_currency currency NOT NULL,
+    rate DECIMAL(20, 10) NOT NULL CHECK (rate > 0),
+    source VARCHAR(50) NOT NULL DEFAULT 'manual',
+    effective_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_exchange_rates_pair ON exchange_rates(quote_currency, base_currency, effective_at DESC);
+CREATE INDEX IF NOT EXISTS idx_exchange_rates_effective ON exchange_rates(effective_at DESC);
+
+-- Rate snapshots (NULL means the transaction was in the base currency or no rate was known)
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS base_currency currency;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS exchange_rate DECIMAL(20, 10);
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS exchange_rate_at TIMESTAMPTZ;
+
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS base_currency currency;
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS exchange_rate DECIMAL(20, 10);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS exchange_rate_at TIMESTAMPTZ;
+
+-- Snapshot the latest effective rate for the row's currency on insert, so
+-- every code path that creates orders or payments records it.
+CREATE OR REPLACE FUNCTION snapshot_exchange_rate()
+RETURNS TRIGGER AS $$
+DECLARE
+    snapshot RECORD;
+BEGIN
+    IF NEW.exchange_rate IS NULL THEN
+        SELECT base_currency, rate, effective_at INTO snapshot
+        FROM exchange_rates
+        WHERE quote_currency = NEW.currency
+          AND effective_at <= NOW()
+        ORDER BY effective_at DESC
+        LIMIT 1;
+
+        IF FOUND THEN
+            NEW.base_currency := snapshot.base_currency;
+            NEW.exchange_rate := snapshot.rate;
+            NEW.exchange_rate_at := snapshot.effective_at;
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS orders_snapshot_exchange_rate ON orders;
+CREATE TRIGGER orders_snapshot_exchange_rate
+    BEFORE INSERT ON orders
+    FOR EACH ROW
+    EXECUTE FUNCTION snapshot_exchange_rate();
+
+DROP TRIGGER IF EXISTS payments_snapshot_exchange_rate ON payments;
+CREATE TRIGGER payments_snapshot_exchange_rate
+    BEFORE INSERT ON payments
+    FOR EACH ROW
+    EXECUTE FUNCTION snapshot_exchange_rate();
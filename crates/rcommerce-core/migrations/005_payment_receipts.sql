@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migration: Payment Receipts
+-- ============================================================================
+-- Keeps the receipt URL returned by the payment gateway so customers can
+-- reach their gateway receipts from the storefront account area without an
+-- admin-scoped key.
+-- ============================================================================
+
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS receipt_url TEXT;
+
+-- Payments are looked up by the gateway's id when an action completes
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payments_gateway_payment_id
+    ON payments(gateway, gateway_payment_id)
+    WHERE gateway_payment_id IS NOT NULL;
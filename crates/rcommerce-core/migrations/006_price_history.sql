@@ -0,0 +1,134 @@
+-- ============================================================================
+-- Migration: Price History (EU Omnibus Directive)
+-- ============================================================================
+-- Records every price change for products and variants so that price
+-- reductions can be announced with the lowest price of the prior 30 days,
+-- and so the store can show its price history during audits.
+--
+-- Rows are written by triggers, so every code path that changes a price
+-- (API, imports, CLI, direct SQL) is recorded.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS price_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL for the product's own price
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    price DECIMAL(20, 2) NOT NULL,
+    compare_at_price DECIMAL(20, 2),
+    currency currency NOT NULL,
+    -- 'created', 'updated' or 'baseline' (price in effect when tracking started)
+    source VARCHAR(20) NOT NULL DEFAULT 'updated',
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_product ON price_history(product_id, variant_id, changed_at DESC);
+
+-- Record product price changes
+CREATE OR REPLACE FUNCTION record_product_price()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT'
+        OR NEW.price IS DISTINCT FROM OLD.price
+        OR NEW.compare_at_price IS DISTINCT FROM OLD.compare_at_price
+        OR NEW.currency IS DISTINCT FROM OLD.currency
+    THEN
+        INSERT INTO price_history (product_id, variant_id, price, compare_at_price, currency, source)
+        VALUES (
+            NEW.id, NULL, NEW.price, NEW.compare_at_price, NEW.currency,
+            CASE WHEN TG_OP = 'INSERT' THEN 'created' ELSE 'updated' END
+        );
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_record_price ON products;
+CREATE TRIGGER products_record_price
+    AFTER INSERT OR UPDATE ON products
+    FOR EACH ROW
+    EXECUTE FUNCTION record_product_price();
+
+-- Record variant price changes
+CREATE OR REPLACE FUNCTION record_variant_price()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT'
+        OR NEW.price IS DISTINCT FROM OLD.price
+        OR NEW.compare_at_price IS DISTINCT FROM OLD.compare_at_price
+        OR NEW.currency IS DISTINCT FROM OLD.currency
+    THEN
+        INSERT INTO price_history (product_id, variant_id, price, compare_at_price, currency, source)
+        VALUES (
+            NEW.product_id, NEW.id, NEW.price, NEW.compare_at_price, NEW.currency,
+            CASE WHEN TG_OP = 'INSERT' THEN 'created' ELSE 'updated' END
+        );
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS product_variants_record_price ON product_variants;
+CREATE TRIGGER product_variants_record_price
+    AFTER INSERT OR UPDATE ON product_variants
+    FOR EACH ROW
+    EXECUTE FUNCTION record_variant_price();
+
+-- Baseline: the prices in effect when tracking started
+INSERT INTO price_history (product_id, variant_id, price, compare_at_price, currency, source)
+SELECT p.id, NULL, p.price, p.compare_at_price, p.currency, 'baseline'
+FROM products p
+WHERE NOT EXISTS (SELECT 1 FROM price_history h WHERE h.product_id = p.id AND h.variant_id IS NULL);
+
+INSERT INTO price_history (product_id, variant_id, price, compare_at_price, currency, source)
+SELECT v.product_id, v.id, v.price, v.compare_at_price, v.currency, 'baseline'
+FROM product_variants v
+WHERE NOT EXISTS (SELECT 1 FROM price_history h WHERE h.variant_id = v.id);
+
+-- Lowest price applied during the p_days before the current price took
+-- effect. Falls back to the current price when there is no earlier price
+-- (nothing was reduced).
+CREATE OR REPLACE FUNCTION lowest_prior_price(p_product_id UUID, p_variant_id UUID, p_days INTEGER)
+RETURNS DECIMAL(20, 2) AS $$
+DECLARE
+    current_price DECIMAL(20, 2);
+    price_since TIMESTAMPTZ;
+    window_start TIMESTAMPTZ;
+    lowest DECIMAL(20, 2);
+BEGIN
+    SELECT price INTO current_price
+    FROM price_history
+    WHERE product_id = p_product_id AND variant_id IS NOT DISTINCT FROM p_variant_id
+    ORDER BY changed_at DESC
+    LIMIT 1;
+
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    -- Start of the trailing run of rows at the current price
+    SELECT MIN(changed_at) INTO price_since
+    FROM price_history
+    WHERE product_id = p_product_id AND variant_id IS NOT DISTINCT FROM p_variant_id
+      AND changed_at > COALESCE((
+          SELECT MAX(changed_at) FROM price_history
+          WHERE product_id = p_product_id AND variant_id IS NOT DISTINCT FROM p_variant_id
+            AND price <> current_price
+      ), '-infinity');
+
+    window_start := price_since - make_interval(days => p_days);
+
+    -- Prices changed inside the window, plus the one in effect when it opened
+    SELECT MIN(price) INTO lowest
+    FROM price_history
+    WHERE product_id = p_product_id AND variant_id IS NOT DISTINCT FROM p_variant_id
+      AND changed_at < price_since
+      AND changed_at >= COALESCE((
+          SELECT MAX(changed_at) FROM price_history
+          WHERE product_id = p_product_id AND variant_id IS NOT DISTINCT FROM p_variant_id
+            AND changed_at <= window_start
+      ), window_start);
+
+    RETURN COALESCE(lowest, current_price);
+END;
+$$ LANGUAGE plpgsql STABLE;
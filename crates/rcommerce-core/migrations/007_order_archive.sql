@@ -0,0 +1,97 @@
+-- ============================================================================
+-- Migration: Order Archive
+-- ============================================================================
+-- Finished orders older than a configurable threshold are moved out of the
+-- hot `orders` table so list queries stay fast on mature stores. Each
+-- archived order keeps a snapshot of its full row and of every dependent
+-- row (items, payments, refunds, fulfillments, notes, coupon usages, tax
+-- transactions), so detail endpoints can read through to the archive.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS orders_archive (
+    id UUID PRIMARY KEY,
+    order_number VARCHAR(50) NOT NULL UNIQUE,
+    -- No foreign key: customers may be deleted after their orders are archived
+    customer_id UUID,
+    email VARCHAR(255) NOT NULL,
+    status order_status NOT NULL,
+    currency currency NOT NULL,
+    total DECIMAL(20, 2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Rows as of archival, keyed by source table ("order", "order_items", ...)
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_orders_archive_customer ON orders_archive(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_orders_archive_archived_at ON orders_archive(archived_at);
+
+-- Orders eligible for archival. Orders that still back something live are
+-- skipped: subscriptions, unexpired downloads and usages of active coupons
+-- (which enforce per-customer limits) would otherwise be cascade-deleted.
+CREATE OR REPLACE FUNCTION archivable_orders(p_cutoff TIMESTAMPTZ, p_statuses TEXT[])
+RETURNS SETOF UUID AS $$
+    SELECT o.id
+    FROM orders o
+    WHERE o.status::text = ANY(p_statuses)
+      AND o.draft = false
+      AND COALESCE(o.completed_at, o.cancelled_at, o.updated_at) < p_cutoff
+      AND NOT EXISTS (SELECT 1 FROM subscriptions s WHERE s.order_id = o.id)
+      AND NOT EXISTS (
+          SELECT 1 FROM order_item_downloads d
+          JOIN order_items i ON i.id = d.order_item_id
+          WHERE i.order_id = o.id AND (d.expires_at IS NULL OR d.expires_at > NOW())
+      )
+      AND NOT EXISTS (
+          SELECT 1 FROM coupon_usages u
+          JOIN coupons c ON c.id = u.coupon_id
+          WHERE u.order_id = o.id AND c.is_active AND (c.expires_at IS NULL OR c.expires_at > NOW())
+      )
+    ORDER BY o.created_at;
+$$ LANGUAGE sql STABLE;
+
+-- Move up to p_limit eligible orders into orders_archive and return them.
+-- Dependent rows are removed by the existing ON DELETE CASCADE foreign keys
+-- once their snapshot is stored.
+CREATE OR REPLACE FUNCTION archive_orders(p_cutoff TIMESTAMPTZ, p_statuses TEXT[], p_limit INTEGER)
+RETURNS SETOF orders_archive AS $$
+DECLARE
+    o orders%ROWTYPE;
+    snapshot JSONB;
+    tax JSONB;
+    archived orders_archive%ROWTYPE;
+BEGIN
+    FOR o IN
+        SELECT * FROM orders
+        WHERE id IN (SELECT archivable_orders(p_cutoff, p_statuses) LIMIT p_limit)
+        ORDER BY created_at
+        FOR UPDATE SKIP LOCKED
+    LOOP
+        snapshot := jsonb_build_object(
+            'order', to_jsonb(o),
+            'order_items', (SELECT COALESCE(jsonb_agg(to_jsonb(i) ORDER BY i.created_at), '[]') FROM order_items i WHERE i.order_id = o.id),
+            'payments', (SELECT COALESCE(jsonb_agg(to_jsonb(p) ORDER BY p.created_at), '[]') FROM payments p WHERE p.order_id = o.id),
+            'refunds', (SELECT COALESCE(jsonb_agg(to_jsonb(r) ORDER BY r.created_at), '[]') FROM refunds r WHERE r.order_id = o.id),
+            'fulfillments', (SELECT COALESCE(jsonb_agg(to_jsonb(f) ORDER BY f.created_at), '[]') FROM fulfillments f WHERE f.order_id = o.id),
+            'order_notes', (SELECT COALESCE(jsonb_agg(to_jsonb(n) ORDER BY n.created_at), '[]') FROM order_notes n WHERE n.order_id = o.id),
+            'coupon_usages', (SELECT COALESCE(jsonb_agg(to_jsonb(u)), '[]') FROM coupon_usages u WHERE u.order_id = o.id)
+        );
+
+        -- The tax schema (002) is optional
+        IF to_regclass('tax_transactions') IS NOT NULL THEN
+            EXECUTE 'SELECT COALESCE(jsonb_agg(to_jsonb(t)), ''[]'') FROM tax_transactions t WHERE t.order_id = $1'
+                INTO tax USING o.id;
+            snapshot := snapshot || jsonb_build_object('tax_transactions', tax);
+        END IF;
+
+        INSERT INTO orders_archive (id, order_number, customer_id, email, status, currency, total, created_at, completed_at, data)
+        VALUES (o.id, o.order_number, o.customer_id, o.email, o.status, o.currency, o.total, o.created_at, o.completed_at, snapshot)
+        RETURNING * INTO archived;
+
+        DELETE FROM orders WHERE id = o.id;
+
+        RETURN NEXT archived;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Migration: Product Variant Options
+-- ============================================================================
+-- Variants of variable products are defined by option values (Size = "M",
+-- Color = "Red"). product_options holds the option names per product and
+-- product_option_values the value each variant selects.
+-- ============================================================================
+
+ALTER TABLE product_variants ADD COLUMN IF NOT EXISTS barcode VARCHAR(100);
+
+-- One option per name (case-insensitive) per product
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_options_product_name
+    ON product_options(product_id, lower(name));
+
+-- One value per option per variant
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_option_values_variant_option
+    ON product_option_values(variant_id, option_id);
+
+CREATE INDEX IF NOT EXISTS idx_product_option_values_option ON product_option_values(option_id);
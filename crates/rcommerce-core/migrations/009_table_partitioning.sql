@@ -0,0 +1,184 @@
+-- ============================================================================
+-- Migration: Table Partitioning
+-- ============================================================================
+-- Append-mostly tables that grow with order volume are range partitioned by
+-- month on created_at, so old months can be scanned, vacuumed and dropped
+-- independently. Partitions are named <table>_pYYYYMM with UTC month bounds;
+-- rows outside the existing partitions land in <table>_default and are moved
+-- into their month's partition when it is created.
+--
+-- Partitioned: notifications, order_events, orders_archive and (when the tax
+-- schema is installed) tax_transactions.
+--
+-- The hot `orders` table is NOT partitioned: PostgreSQL requires the
+-- partition key in every unique constraint, and items, payments, refunds,
+-- fulfillments and friends all reference orders(id). Old orders leave it
+-- through archival instead (see 007_order_archive.sql), and the archive is
+-- partitioned here.
+--
+-- Future partitions are created by ensure_monthly_partitions(), which the
+-- migrator and the API server's maintenance job call.
+-- ============================================================================
+
+-- Tables whose monthly partitions are maintained automatically
+CREATE TABLE IF NOT EXISTS partitioned_tables (
+    table_name TEXT PRIMARY KEY,
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create the partition of p_parent holding p_month, moving any rows for that
+-- month out of the default partition first. Returns false if it exists.
+CREATE OR REPLACE FUNCTION create_monthly_partition(p_parent TEXT, p_month DATE)
+RETURNS BOOLEAN AS $$
+DECLARE
+    v_start DATE := date_trunc('month', p_month)::date;
+    v_name TEXT := p_parent || '_p' || to_char(date_trunc('month', p_month), 'YYYYMM');
+    v_from TIMESTAMPTZ;
+    v_to TIMESTAMPTZ;
+BEGIN
+    IF to_regclass(v_name) IS NOT NULL THEN
+        RETURN false;
+    END IF;
+
+    v_from := v_start::timestamp AT TIME ZONE 'UTC';
+    v_to := (v_start + INTERVAL '1 month')::timestamp AT TIME ZONE 'UTC';
+
+    EXECUTE format('CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS INCLUDING CONSTRAINTS)', v_name, p_parent);
+
+    IF to_regclass(p_parent || '_default') IS NOT NULL THEN
+        EXECUTE format(
+            'WITH moved AS (DELETE FROM %I WHERE created_at >= $1 AND created_at < $2 RETURNING *) INSERT INTO %I SELECT * FROM moved',
+            p_parent || '_default', v_name
+        ) USING v_from, v_to;
+    END IF;
+
+    EXECUTE format('ALTER TABLE %I ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)', p_parent, v_name, v_from, v_to);
+    RETURN true;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Create partitions from the current month through p_months_ahead months
+-- ahead for every registered table, returning the names of new partitions
+CREATE OR REPLACE FUNCTION ensure_monthly_partitions(p_months_ahead INTEGER)
+RETURNS SETOF TEXT AS $$
+DECLARE
+    t TEXT;
+    m DATE;
+BEGIN
+    FOR t IN SELECT table_name FROM partitioned_tables WHERE to_regclass(table_name) IS NOT NULL ORDER BY table_name LOOP
+        FOR m IN
+            SELECT generate_series(
+                date_trunc('month', NOW() AT TIME ZONE 'UTC'),
+                date_trunc('month', NOW() AT TIME ZONE 'UTC') + make_interval(months => GREATEST(p_months_ahead, 0)),
+                INTERVAL '1 month'
+            )::date
+        LOOP
+            IF create_monthly_partition(t, m) THEN
+                RETURN NEXT t || '_p' || to_char(m, 'YYYYMM');
+            END IF;
+        END LOOP;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Rebuild an existing table as a monthly partitioned table, keeping its
+-- rows, indexes, foreign keys and triggers. The primary key becomes
+-- (id, created_at); unique constraints that do not include created_at are
+-- recreated as plain indexes. No-op for tables that are already partitioned.
+CREATE OR REPLACE FUNCTION convert_to_monthly_partitions(p_table TEXT)
+RETURNS VOID AS $$
+DECLARE
+    v_old TEXT := p_table || '_unpartitioned';
+    v_indexes TEXT[];
+    v_fkeys TEXT[];
+    v_triggers TEXT[];
+    v_def TEXT;
+    m DATE;
+BEGIN
+    IF to_regclass(p_table) IS NULL THEN
+        RETURN;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM pg_partitioned_table WHERE partrelid = to_regclass(p_table)) THEN
+        -- Non-primary indexes; unique ones are demoted unless they cover created_at
+        SELECT COALESCE(array_agg(
+                   CASE WHEN i.indisunique AND NOT pg_get_indexdef(i.indexrelid) ~ '\mcreated_at\M'
+                        THEN regexp_replace(pg_get_indexdef(i.indexrelid), '^CREATE UNIQUE INDEX', 'CREATE INDEX')
+                        ELSE pg_get_indexdef(i.indexrelid)
+                   END), '{}')
+        INTO v_indexes
+        FROM pg_index i
+        WHERE i.indrelid = to_regclass(p_table) AND NOT i.indisprimary;
+
+        SELECT COALESCE(array_agg(format('ALTER TABLE %I ADD CONSTRAINT %I %s', p_table, conname, pg_get_constraintdef(oid))), '{}')
+        INTO v_fkeys
+        FROM pg_constraint
+        WHERE conrelid = to_regclass(p_table) AND contype = 'f';
+
+        SELECT COALESCE(array_agg(pg_get_triggerdef(oid)), '{}')
+        INTO v_triggers
+        FROM pg_trigger
+        WHERE tgrelid = to_regclass(p_table) AND NOT tgisinternal;
+
+        EXECUTE format('ALTER TABLE %I RENAME TO %I', p_table, v_old);
+        EXECUTE format('CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS INCLUDING CONSTRAINTS) PARTITION BY RANGE (created_at)', p_table, v_old);
+        EXECUTE format('CREATE TABLE %I PARTITION OF %I DEFAULT', p_table || '_default', p_table);
+
+        -- Partitions for the months already holding data, so nothing is left in default
+        FOR m IN EXECUTE format(
+            'SELECT DISTINCT date_trunc(''month'', created_at AT TIME ZONE ''UTC'')::date FROM %I', v_old
+        ) LOOP
+            PERFORM create_monthly_partition(p_table, m);
+        END LOOP;
+
+        EXECUTE format('INSERT INTO %I SELECT * FROM %I', p_table, v_old);
+        EXECUTE format('DROP TABLE %I', v_old);
+
+        EXECUTE format('ALTER TABLE %I ADD PRIMARY KEY (id, created_at)', p_table);
+        FOREACH v_def IN ARRAY v_indexes LOOP
+            EXECUTE v_def;
+        END LOOP;
+        FOREACH v_def IN ARRAY v_fkeys LOOP
+            EXECUTE v_def;
+        END LOOP;
+        FOREACH v_def IN ARRAY v_triggers LOOP
+            EXECUTE v_def;
+        END LOOP;
+    END IF;
+
+    INSERT INTO partitioned_tables (table_name) VALUES (p_table) ON CONFLICT DO NOTHING;
+END;
+$$ LANGUAGE plpgsql;
+
+-- ============================================================================
+-- ORDER EVENTS
+-- ============================================================================
+-- Append-only log of order lifecycle events. No foreign key to orders: events
+-- outlive archived orders, and partitioned tables cannot be referenced by id.
+
+CREATE TABLE IF NOT EXISTS order_events (
+    id UUID NOT NULL DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+CREATE TABLE IF NOT EXISTS order_events_default PARTITION OF order_events DEFAULT;
+
+CREATE INDEX IF NOT EXISTS idx_order_events_order ON order_events(order_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_order_events_type ON order_events(event_type, created_at);
+
+INSERT INTO partitioned_tables (table_name) VALUES ('order_events') ON CONFLICT DO NOTHING;
+
+-- ============================================================================
+-- EXISTING TABLES
+-- ============================================================================
+
+SELECT convert_to_monthly_partitions('notifications');
+SELECT convert_to_monthly_partitions('orders_archive');
+-- Skipped when the optional tax schema (002) is not installed
+SELECT convert_to_monthly_partitions('tax_transactions');
+
+SELECT ensure_monthly_partitions(3);
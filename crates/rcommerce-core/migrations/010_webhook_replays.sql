@@ -0,0 +1,50 @@
+-- ============================================================================
+-- Migration: Webhook Replays
+-- ============================================================================
+-- Admins re-send outbound webhook events to an endpoint after a consumer
+-- outage. A replay selects the endpoint's original deliveries in a time range
+-- (optionally only some event types, or only events never acknowledged),
+-- re-sends each distinct event once at a limited rate and tracks progress
+-- here. Replayed deliveries are logged in webhook_deliveries with a link to
+-- the replay and to the original delivery.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'webhook_replay_status') THEN
+        CREATE TYPE webhook_replay_status AS ENUM ('pending', 'running', 'completed', 'failed', 'cancelled');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS webhook_replays (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    webhook_id UUID NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    -- Empty means every event type
+    event_types VARCHAR(100)[] NOT NULL DEFAULT '{}',
+    from_time TIMESTAMPTZ NOT NULL,
+    to_time TIMESTAMPTZ NOT NULL,
+    failed_only BOOLEAN NOT NULL DEFAULT false,
+    rate_per_second INTEGER NOT NULL CHECK (rate_per_second > 0),
+    status webhook_replay_status NOT NULL DEFAULT 'pending',
+    total_events INTEGER NOT NULL DEFAULT 0,
+    sent INTEGER NOT NULL DEFAULT 0,
+    succeeded INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    -- Last replayed original delivery, so progress survives restarts
+    cursor_created_at TIMESTAMPTZ,
+    cursor_id UUID,
+    error_message TEXT,
+    requested_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    CHECK (to_time > from_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_replays_webhook ON webhook_replays(webhook_id, created_at DESC);
+
+ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS replay_id UUID REFERENCES webhook_replays(id) ON DELETE SET NULL;
+ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS original_delivery_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_replay ON webhook_deliveries(replay_id) WHERE replay_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_created ON webhook_deliveries(webhook_id, created_at);
@@ -0,0 +1,11 @@
+-- ============================================================================
+-- Migration: Webhook Transforms
+-- ============================================================================
+-- Optional per-endpoint payload template (Handlebars) and custom headers, so
+-- consumers with fixed payload shapes (Slack, Zapier, legacy ERPs) can be
+-- served without middleware. Deliveries still log the canonical payload.
+-- ============================================================================
+
+ALTER TABLE webhooks ADD COLUMN IF NOT EXISTS payload_template TEXT;
+-- Header name -> value template
+ALTER TABLE webhooks ADD COLUMN IF NOT EXISTS headers JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,38 @@
+-- ============================================================================
+-- Migration: Subscription Plans
+-- ============================================================================
+-- Reusable plans (product, interval, price, trial) that customers subscribe
+-- to. A subscription copies the plan's terms when it is created, so later
+-- price changes only apply to new subscribers; plan_id records where the
+-- terms came from.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS subscription_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE SET NULL,
+    interval subscription_interval NOT NULL,
+    interval_count INTEGER NOT NULL DEFAULT 1 CHECK (interval_count BETWEEN 1 AND 12),
+    currency currency NOT NULL DEFAULT 'USD',
+    amount DECIMAL(20, 2) NOT NULL CHECK (amount >= 0),
+    setup_fee DECIMAL(20, 2),
+    trial_days INTEGER NOT NULL DEFAULT 0 CHECK (trial_days >= 0),
+    min_cycles INTEGER,
+    max_cycles INTEGER,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_subscription_plans_product ON subscription_plans(product_id);
+CREATE INDEX IF NOT EXISTS idx_subscription_plans_active ON subscription_plans(is_active) WHERE is_active;
+
+DROP TRIGGER IF EXISTS subscription_plans_updated_at ON subscription_plans;
+CREATE TRIGGER subscription_plans_updated_at
+    BEFORE UPDATE ON subscription_plans
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE subscriptions ADD COLUMN IF NOT EXISTS plan_id UUID REFERENCES subscription_plans(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_subscriptions_plan ON subscriptions(plan_id) WHERE plan_id IS NOT NULL;
@@ -0,0 +1,62 @@
+-- ============================================================================
+-- Migration: Stock Adjustment Requests
+-- ============================================================================
+-- Manual stock corrections go through an adjustment request. Small changes
+-- are posted immediately; changes above the configured thresholds wait for a
+-- second staff member to approve them. Approved adjustments are posted as
+-- stock_movements of type 'adjustment'. Every step is kept in
+-- stock_adjustment_audit.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'stock_adjustment_status') THEN
+        CREATE TYPE stock_adjustment_status AS ENUM ('pending', 'approved', 'rejected', 'cancelled');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS stock_adjustment_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES inventory_locations(id) ON DELETE CASCADE,
+    quantity_change INTEGER NOT NULL CHECK (quantity_change <> 0),
+    -- Stock level when requested, and before/after posting
+    quantity_at_request INTEGER NOT NULL,
+    quantity_before INTEGER,
+    quantity_after INTEGER,
+    reason VARCHAR(255) NOT NULL,
+    notes TEXT,
+    status stock_adjustment_status NOT NULL DEFAULT 'pending',
+    requires_approval BOOLEAN NOT NULL,
+    requested_by UUID NOT NULL REFERENCES customers(id),
+    decided_by UUID REFERENCES customers(id),
+    decided_at TIMESTAMPTZ,
+    decision_notes TEXT,
+    stock_movement_id UUID REFERENCES stock_movements(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_adjustment_requests_pending
+    ON stock_adjustment_requests(created_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_stock_adjustment_requests_product
+    ON stock_adjustment_requests(product_id, created_at DESC);
+
+DROP TRIGGER IF EXISTS update_stock_adjustment_requests_updated_at ON stock_adjustment_requests;
+CREATE TRIGGER update_stock_adjustment_requests_updated_at
+    BEFORE UPDATE ON stock_adjustment_requests
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS stock_adjustment_audit (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    adjustment_id UUID NOT NULL REFERENCES stock_adjustment_requests(id) ON DELETE CASCADE,
+    action VARCHAR(20) NOT NULL CHECK (action IN ('requested', 'approved', 'rejected', 'cancelled', 'posted')),
+    -- NULL for automatic steps
+    actor_id UUID REFERENCES customers(id),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_adjustment_audit_adjustment
+    ON stock_adjustment_audit(adjustment_id, created_at);
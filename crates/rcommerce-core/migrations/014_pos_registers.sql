@@ -0,0 +1,74 @@
+-- ============================================================================
+-- Migration: POS Registers and Register Sessions
+-- ============================================================================
+-- Cash drawer reconciliation for in-store (POS) sales. A register session is
+-- opened with a cash float, records every tender taken or refunded at the
+-- register plus cash paid in or out of the drawer, and is closed with the
+-- counted cash. Expected cash and the over/short amount are stored on close
+-- and reported in the session's Z-report.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'register_session_status') THEN
+        CREATE TYPE register_session_status AS ENUM ('open', 'closed');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'register_transaction_kind') THEN
+        CREATE TYPE register_transaction_kind AS ENUM ('sale', 'refund', 'paid_in', 'paid_out');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS pos_registers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    location_id UUID REFERENCES inventory_locations(id) ON DELETE SET NULL,
+    currency currency NOT NULL DEFAULT 'USD',
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_pos_registers_updated_at ON pos_registers;
+CREATE TRIGGER update_pos_registers_updated_at
+    BEFORE UPDATE ON pos_registers
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS register_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    register_id UUID NOT NULL REFERENCES pos_registers(id) ON DELETE CASCADE,
+    status register_session_status NOT NULL DEFAULT 'open',
+    currency currency NOT NULL,
+    opening_float DECIMAL(20, 2) NOT NULL CHECK (opening_float >= 0),
+    opened_by UUID NOT NULL REFERENCES customers(id),
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    opening_notes TEXT,
+    -- Set when the session is closed
+    counted_cash DECIMAL(20, 2),
+    expected_cash DECIMAL(20, 2),
+    over_short DECIMAL(20, 2),
+    closed_by UUID REFERENCES customers(id),
+    closed_at TIMESTAMPTZ,
+    closing_notes TEXT
+);
+
+-- One open session per register
+CREATE UNIQUE INDEX IF NOT EXISTS idx_register_sessions_open
+    ON register_sessions(register_id) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_register_sessions_register
+    ON register_sessions(register_id, opened_at DESC);
+
+CREATE TABLE IF NOT EXISTS register_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES register_sessions(id) ON DELETE CASCADE,
+    kind register_transaction_kind NOT NULL,
+    -- Tender, e.g. cash, card, gift_card; paid in/out is always cash
+    method VARCHAR(50) NOT NULL,
+    amount DECIMAL(20, 2) NOT NULL CHECK (amount > 0),
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    reference VARCHAR(255),
+    recorded_by UUID NOT NULL REFERENCES customers(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_register_transactions_session
+    ON register_transactions(session_id, created_at);
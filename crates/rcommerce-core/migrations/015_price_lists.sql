@@ -0,0 +1,53 @@
+-- ============================================================================
+-- Migration: Price Lists
+-- ============================================================================
+-- Per-currency (and optionally per-region) product prices. A product's own
+-- price and currency remain its base price; a price list overrides it for
+-- shoppers paying in the list's currency, optionally only in some countries.
+-- Products without a price in the requested currency are converted from
+-- their base price with the configured FX rate provider.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS price_lists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    currency currency NOT NULL,
+    -- ISO country codes the list applies to; empty applies everywhere
+    countries TEXT[] NOT NULL DEFAULT '{}',
+    -- Higher priority wins when several lists match
+    priority INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_lists_currency ON price_lists(currency) WHERE is_active;
+
+DROP TRIGGER IF EXISTS update_price_lists_updated_at ON price_lists;
+CREATE TRIGGER update_price_lists_updated_at
+    BEFORE UPDATE ON price_lists
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS price_list_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    price_list_id UUID NOT NULL REFERENCES price_lists(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL prices the product and all of its variants
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    price DECIMAL(20, 2) NOT NULL CHECK (price >= 0),
+    compare_at_price DECIMAL(20, 2) CHECK (compare_at_price >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One price per product (or variant) per list
+CREATE UNIQUE INDEX IF NOT EXISTS idx_price_list_prices_product
+    ON price_list_prices(price_list_id, product_id) WHERE variant_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_price_list_prices_variant
+    ON price_list_prices(price_list_id, product_id, variant_id) WHERE variant_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_price_list_prices_lookup ON price_list_prices(product_id, variant_id);
+
+DROP TRIGGER IF EXISTS update_price_list_prices_updated_at ON price_list_prices;
+CREATE TRIGGER update_price_list_prices_updated_at
+    BEFORE UPDATE ON price_list_prices
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
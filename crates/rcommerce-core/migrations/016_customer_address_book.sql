@@ -0,0 +1,31 @@
+-- ============================================================================
+-- Migration: Customer Address Book
+-- ============================================================================
+-- Customers keep several labelled addresses ("Home", "Office") with one
+-- default shipping and one default billing address. Addresses checked with a
+-- shipping carrier record which carrier validated them and when.
+-- ============================================================================
+
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS label VARCHAR(50);
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS residential BOOLEAN;
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS validation_provider VARCHAR(50);
+ALTER TABLE addresses ADD COLUMN IF NOT EXISTS validated_at TIMESTAMPTZ;
+
+-- Keep only the most recently updated default of each kind per customer
+UPDATE addresses a SET is_default_shipping = false
+WHERE a.is_default_shipping AND EXISTS (
+    SELECT 1 FROM addresses b
+    WHERE b.customer_id = a.customer_id AND b.is_default_shipping
+      AND (b.updated_at, b.id) > (a.updated_at, a.id)
+);
+UPDATE addresses a SET is_default_billing = false
+WHERE a.is_default_billing AND EXISTS (
+    SELECT 1 FROM addresses b
+    WHERE b.customer_id = a.customer_id AND b.is_default_billing
+      AND (b.updated_at, b.id) > (a.updated_at, a.id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_addresses_default_shipping
+    ON addresses(customer_id) WHERE is_default_shipping;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_addresses_default_billing
+    ON addresses(customer_id) WHERE is_default_billing;
@@ -0,0 +1,77 @@
+-- ============================================================================
+-- Migration: Order Add-ons
+-- ============================================================================
+-- Optional extras shoppers pick at checkout: gift wrap, a gift message, an
+-- assembly service. Order-level add-ons apply once to the whole order,
+-- item-level ones to a single line (optionally charged per unit). Each
+-- add-on can carry its own tax category and instructions for the warehouse,
+-- which are printed on the order's pick list.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'addon_scope') THEN
+        CREATE TYPE addon_scope AS ENUM ('order', 'item');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS addons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    scope addon_scope NOT NULL,
+    price DECIMAL(20, 2) NOT NULL CHECK (price >= 0),
+    -- Item add-ons only: charge once per unit of the line rather than once
+    per_unit BOOLEAN NOT NULL DEFAULT false,
+    -- Taxed like the products in this category; NULL uses the default rate
+    tax_category_id UUID,
+    -- Whether shoppers can attach a message (e.g. a gift message)
+    accepts_message BOOLEAN NOT NULL DEFAULT false,
+    max_message_length INTEGER NOT NULL DEFAULT 250 CHECK (max_message_length > 0),
+    fulfillment_instructions TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_addons_updated_at ON addons;
+CREATE TRIGGER update_addons_updated_at
+    BEFORE UPDATE ON addons
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS cart_addons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    cart_id UUID NOT NULL REFERENCES carts(id) ON DELETE CASCADE,
+    -- NULL for order-level add-ons
+    cart_item_id UUID REFERENCES cart_items(id) ON DELETE CASCADE,
+    addon_id UUID NOT NULL REFERENCES addons(id) ON DELETE CASCADE,
+    message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Each add-on at most once per cart (order-level) or per line (item-level)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cart_addons_order
+    ON cart_addons(cart_id, addon_id) WHERE cart_item_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cart_addons_item
+    ON cart_addons(cart_item_id, addon_id) WHERE cart_item_id IS NOT NULL;
+
+-- Add-ons as sold, with price, tax and instructions as of checkout
+CREATE TABLE IF NOT EXISTS order_addons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    order_item_id UUID REFERENCES order_items(id) ON DELETE SET NULL,
+    addon_id UUID REFERENCES addons(id) ON DELETE SET NULL,
+    code VARCHAR(50) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(20, 2) NOT NULL,
+    total DECIMAL(20, 2) NOT NULL,
+    tax_amount DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    message TEXT,
+    fulfillment_instructions TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_addons_order ON order_addons(order_id);
@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migration: Delivery Preferences
+-- ============================================================================
+-- Instructions for the driver and, where the carrier service supports
+-- scheduled delivery, the date and time window booked at checkout. They
+-- are passed to the carrier when the label is created and printed on the
+-- packing slip.
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS delivery_instructions TEXT;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS delivery_date DATE;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS delivery_window_start TIME;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS delivery_window_end TIME;
+
+-- Warehouse view of upcoming booked deliveries
+CREATE INDEX IF NOT EXISTS idx_orders_delivery_date ON orders(delivery_date) WHERE delivery_date IS NOT NULL;
@@ -0,0 +1,80 @@
+-- ============================================================================
+-- Migration: Returns (RMA)
+-- ============================================================================
+-- Customers request a return for items on an order; staff approve or reject
+-- it. Approved returns get a prepaid return label from a shipping carrier
+-- and are refunded through the payment gateway once received. Every status
+-- change is kept in return_events.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'return_status') THEN
+        CREATE TYPE return_status AS ENUM ('requested', 'approved', 'rejected', 'received', 'refunded', 'cancelled');
+    END IF;
+END$$;
+
+CREATE SEQUENCE IF NOT EXISTS return_number_seq START 1001;
+
+CREATE TABLE IF NOT EXISTS returns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    rma_number VARCHAR(50) NOT NULL UNIQUE DEFAULT ('RMA-' || nextval('return_number_seq')),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    customer_id UUID REFERENCES customers(id) ON DELETE SET NULL,
+    status return_status NOT NULL DEFAULT 'requested',
+    reason VARCHAR(255) NOT NULL,
+    customer_notes TEXT,
+    -- Shown to the customer when rejected
+    decision_notes TEXT,
+    currency VARCHAR(3) NOT NULL,
+    refund_amount DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    -- Return label
+    label_provider VARCHAR(50),
+    label_service VARCHAR(100),
+    tracking_number VARCHAR(255),
+    label_url TEXT,
+    -- Gateway refund, once refunded
+    refund_id UUID REFERENCES refunds(id) ON DELETE SET NULL,
+    gateway_refund_id VARCHAR(255),
+    decided_by UUID REFERENCES customers(id),
+    decided_at TIMESTAMPTZ,
+    received_at TIMESTAMPTZ,
+    refunded_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_returns_order ON returns(order_id);
+CREATE INDEX IF NOT EXISTS idx_returns_customer ON returns(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_returns_open ON returns(created_at)
+    WHERE status IN ('requested', 'approved', 'received');
+
+DROP TRIGGER IF EXISTS update_returns_updated_at ON returns;
+CREATE TRIGGER update_returns_updated_at
+    BEFORE UPDATE ON returns
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS return_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    return_id UUID NOT NULL REFERENCES returns(id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- Refund per unit: the line total (with tax) over its quantity
+    unit_refund DECIMAL(20, 2) NOT NULL,
+    reason VARCHAR(255),
+    UNIQUE (return_id, order_item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_return_items_order_item ON return_items(order_item_id);
+
+CREATE TABLE IF NOT EXISTS return_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    return_id UUID NOT NULL REFERENCES returns(id) ON DELETE CASCADE,
+    status return_status NOT NULL,
+    -- NULL for automatic steps
+    actor_id UUID REFERENCES customers(id),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_return_events_return ON return_events(return_id, created_at);
@@ -0,0 +1,42 @@
+-- ============================================================================
+-- Migration: Staff Roles and Permissions
+-- ============================================================================
+-- Named roles granting `resource:action` permissions (the same scope format
+-- as API keys, e.g. `orders:write`, `reports:read`, or `admin` for
+-- everything). Roles are assigned to customer accounts on top of their base
+-- `customers.role`; admin routes check the union of both on every request.
+-- System roles are seeded here and cannot be deleted.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    description TEXT,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    is_system BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_roles_updated_at ON roles;
+CREATE TRIGGER update_roles_updated_at
+    BEFORE UPDATE ON roles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS customer_roles (
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    assigned_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (customer_id, role_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_customer_roles_role ON customer_roles(role_id);
+
+INSERT INTO roles (name, description, permissions, is_system) VALUES
+    ('administrator', 'Full access to everything', ARRAY['admin'], true),
+    ('order_manager', 'Orders, returns and fulfillment', ARRAY['orders:write', 'customers:read', 'payments:read', 'inventory:read'], true),
+    ('catalog_manager', 'Products, pricing and stock', ARRAY['products:write', 'inventory:write', 'imports:write', 'exports:read'], true),
+    ('support_agent', 'Customer service', ARRAY['orders:write', 'customers:write', 'payments:read'], true),
+    ('analyst', 'Reports and exports', ARRAY['reports:read', 'exports:read'], true)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,10 @@
+-- ============================================================================
+-- Migration: Custom Checkout Fields
+-- ============================================================================
+-- Answers to the merchant's extra checkout fields (`[checkout_fields]` in
+-- the config: B2B VAT number, delivery phone, "how did you hear about us"),
+-- keyed by field key. They are validated against the configured schema at
+-- checkout, and archived with the order row.
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}'::jsonb;
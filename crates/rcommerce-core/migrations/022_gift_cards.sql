@@ -0,0 +1,64 @@
+-- ============================================================================
+-- Migration: Gift Cards
+-- ============================================================================
+-- Gift cards are issued for a fixed or custom amount and redeemed at
+-- checkout, alone or together with a card payment, until the balance runs
+-- out or the card expires. Every balance change (issue, redemption,
+-- reversal of a failed checkout, manual adjustment, expiry) is kept in
+-- gift_card_transactions.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'gift_card_status') THEN
+        CREATE TYPE gift_card_status AS ENUM ('active', 'disabled', 'expired');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'gift_card_transaction_kind') THEN
+        CREATE TYPE gift_card_transaction_kind AS ENUM ('issue', 'redeem', 'reverse', 'adjust', 'expire');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS gift_cards (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- XXXX-XXXX-XXXX-XXXX, entered by the customer at checkout
+    code VARCHAR(32) NOT NULL UNIQUE,
+    currency VARCHAR(3) NOT NULL,
+    initial_balance DECIMAL(20, 2) NOT NULL CHECK (initial_balance > 0),
+    balance DECIMAL(20, 2) NOT NULL CHECK (balance >= 0),
+    status gift_card_status NOT NULL DEFAULT 'active',
+    expires_at TIMESTAMPTZ,
+    recipient_email VARCHAR(255),
+    recipient_name VARCHAR(255),
+    message TEXT,
+    -- Staff note, not shown to the customer
+    note TEXT,
+    issued_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_gift_cards_created ON gift_cards(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_gift_cards_expiring ON gift_cards(expires_at)
+    WHERE status = 'active' AND expires_at IS NOT NULL;
+
+DROP TRIGGER IF EXISTS update_gift_cards_updated_at ON gift_cards;
+CREATE TRIGGER update_gift_cards_updated_at
+    BEFORE UPDATE ON gift_cards
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS gift_card_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    gift_card_id UUID NOT NULL REFERENCES gift_cards(id) ON DELETE CASCADE,
+    kind gift_card_transaction_kind NOT NULL,
+    -- Positive adds to the balance, negative spends it
+    amount DECIMAL(20, 2) NOT NULL,
+    balance_after DECIMAL(20, 2) NOT NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    -- None for checkout and automatic expiry
+    actor_id UUID REFERENCES customers(id) ON DELETE SET NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_gift_card_transactions_card ON gift_card_transactions(gift_card_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_gift_card_transactions_order ON gift_card_transactions(order_id) WHERE order_id IS NOT NULL;
@@ -0,0 +1,47 @@
+-- ============================================================================
+-- Migration: Flash Sales
+-- ============================================================================
+-- A flash sale puts a capped quantity of one product (or variant) on sale
+-- for a short window. While it is live, stock counters, purchase tokens,
+-- the waiting room and per-customer counts live in Redis so that the rush
+-- never reaches Postgres; this table keeps the definition and, once the
+-- sale is closed, how many units sold.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'flash_sale_status') THEN
+        CREATE TYPE flash_sale_status AS ENUM ('draft', 'live', 'closed');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS flash_sales (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL covers every variant of the product
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    status flash_sale_status NOT NULL DEFAULT 'draft',
+    -- Units that can be sold during the sale
+    stock_limit INTEGER NOT NULL CHECK (stock_limit > 0),
+    -- Units one customer can buy (0 = no limit)
+    per_customer_limit INTEGER NOT NULL DEFAULT 1 CHECK (per_customer_limit >= 0),
+    -- Purchase tokens held at once; everyone else waits in the queue
+    max_active_tokens INTEGER NOT NULL CHECK (max_active_tokens > 0),
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    -- Recorded when the sale is closed
+    sold_quantity INTEGER NOT NULL DEFAULT 0,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_flash_sales_product ON flash_sales(product_id);
+CREATE INDEX IF NOT EXISTS idx_flash_sales_starts ON flash_sales(starts_at DESC);
+
+DROP TRIGGER IF EXISTS update_flash_sales_updated_at ON flash_sales;
+CREATE TRIGGER update_flash_sales_updated_at
+    BEFORE UPDATE ON flash_sales
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
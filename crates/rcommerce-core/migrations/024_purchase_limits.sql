@@ -0,0 +1,76 @@
+-- ============================================================================
+-- Migration: Purchase Limits and Allocation Lists
+-- ============================================================================
+-- Purchase limit rules cap how many units of a product (or one variant) a
+-- customer can buy, for all time or within a rolling window. A rule can
+-- also count every order shipped to the same household, matched on a hash
+-- of the normalized shipping address kept on the order.
+--
+-- Allocation lists hold a product back for their members: between opens_at
+-- and public_at only listed customers can buy it, and afterwards anyone
+-- can. A list without public_at keeps the product members-only.
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS household_key VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_orders_household_key ON orders(household_key)
+    WHERE household_key IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS purchase_limit_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL covers every variant of the product
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    max_quantity INTEGER NOT NULL CHECK (max_quantity > 0),
+    -- Rolling window in hours; NULL counts every past order
+    window_hours INTEGER CHECK (window_hours > 0),
+    -- Also count orders shipped to the same address
+    per_household BOOLEAN NOT NULL DEFAULT false,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_limit_rules_product ON purchase_limit_rules(product_id)
+    WHERE is_active;
+
+CREATE TABLE IF NOT EXISTS allocation_lists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL covers every variant of the product
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    -- Members can buy from here
+    opens_at TIMESTAMPTZ NOT NULL,
+    -- Everyone can buy from here; NULL keeps the product members-only
+    public_at TIMESTAMPTZ,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (public_at IS NULL OR public_at > opens_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_allocation_lists_product ON allocation_lists(product_id)
+    WHERE is_active;
+
+CREATE TABLE IF NOT EXISTS allocation_list_members (
+    list_id UUID NOT NULL REFERENCES allocation_lists(id) ON DELETE CASCADE,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (list_id, customer_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_allocation_list_members_customer ON allocation_list_members(customer_id);
+
+DROP TRIGGER IF EXISTS update_purchase_limit_rules_updated_at ON purchase_limit_rules;
+CREATE TRIGGER update_purchase_limit_rules_updated_at
+    BEFORE UPDATE ON purchase_limit_rules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_allocation_lists_updated_at ON allocation_lists;
+CREATE TRIGGER update_allocation_lists_updated_at
+    BEFORE UPDATE ON allocation_lists
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
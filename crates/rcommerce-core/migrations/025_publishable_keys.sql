@@ -0,0 +1,28 @@
+-- ============================================================================
+-- Migration: Publishable Storefront Keys
+-- ============================================================================
+-- Publishable keys are meant to ship in storefront JavaScript. They only
+-- open the read-only /storefront endpoints, only for requests from one of
+-- their allowed origins, and have their own rate limit. Secret keys keep
+-- working as before and are refused by the storefront endpoints.
+--
+-- Rotating a key issues a successor with the same settings and lets the
+-- old key expire after a grace period; rotated_to points at the successor.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'api_key_type') THEN
+        CREATE TYPE api_key_type AS ENUM ('secret', 'publishable');
+    END IF;
+END$$;
+
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS key_type api_key_type NOT NULL DEFAULT 'secret';
+
+-- Origins a publishable key may be used from: "https://shop.example.com",
+-- "shop.example.com" (any scheme) or "*.example.com" (any subdomain)
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS allowed_origins TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS rotated_to UUID REFERENCES api_keys(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_key_type ON api_keys(key_type);
@@ -0,0 +1,47 @@
+-- ============================================================================
+-- Migration: Idempotency Keys
+-- ============================================================================
+-- A POST with an Idempotency-Key header is recorded here before it runs.
+-- Retries with the same key and the same request get the stored response
+-- instead of running again, so a client that lost the response to "complete
+-- checkout" or "refund" can safely send it again. Keys are scoped to the
+-- caller and kept for [idempotency] key_ttl_hours.
+--
+-- lock_id changes whenever a request takes the key, so a request that
+-- outlived lock_timeout_secs cannot overwrite the one that replaced it.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'idempotency_key_status') THEN
+        CREATE TYPE idempotency_key_status AS ENUM ('in_progress', 'completed');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    -- Customer ID for authenticated requests
+    scope VARCHAR(64) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    -- SHA-256 of method, path and body
+    request_hash CHAR(64) NOT NULL,
+    status idempotency_key_status NOT NULL DEFAULT 'in_progress',
+    lock_id UUID NOT NULL DEFAULT gen_random_uuid(),
+    response_status INTEGER,
+    response_content_type VARCHAR(255),
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
+
+DROP TRIGGER IF EXISTS update_idempotency_keys_updated_at ON idempotency_keys;
+CREATE TRIGGER update_idempotency_keys_updated_at
+    BEFORE UPDATE ON idempotency_keys
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,37 @@
+-- ============================================================================
+-- Migration: API Key IP Allowlists and Access Denial Audit Log
+-- ============================================================================
+-- An API key with allowed_ips can only be used from those addresses or
+-- networks (CIDR); keys without any keep working from anywhere.
+--
+-- access_denials records requests refused by the admin and API key access
+-- controls: admin requests without a verified client certificate (when
+-- [tls.client_auth] requires one) and API keys used from outside their
+-- allowlist.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'access_denial_reason') THEN
+        CREATE TYPE access_denial_reason AS ENUM ('client_certificate_required', 'ip_not_allowed');
+    END IF;
+END$$;
+
+-- Addresses ("203.0.113.7") or networks ("10.0.0.0/8", "2001:db8::/32")
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS allowed_ips TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE TABLE IF NOT EXISTS access_denials (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reason access_denial_reason NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    client_ip VARCHAR(45),
+    api_key_id UUID REFERENCES api_keys(id) ON DELETE SET NULL,
+    customer_id UUID REFERENCES customers(id) ON DELETE SET NULL,
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_denials_created_at ON access_denials(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_access_denials_api_key ON access_denials(api_key_id)
+    WHERE api_key_id IS NOT NULL;
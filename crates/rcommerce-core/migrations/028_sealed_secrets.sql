@@ -0,0 +1,10 @@
+-- ============================================================================
+-- Migration: Envelope-Encrypted Secrets
+-- ============================================================================
+-- With a master key configured ([secrets]), webhook signing secrets are
+-- stored sealed ("enc:v1:<key id>:<wrapped data key>:<ciphertext>"), which
+-- is longer than the old VARCHAR(255). Existing plain-text values are
+-- sealed by `rcommerce secrets rotate` or when the server starts.
+-- ============================================================================
+
+ALTER TABLE webhooks ALTER COLUMN secret TYPE TEXT;
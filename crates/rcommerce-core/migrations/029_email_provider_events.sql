@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migration: Email Provider Events
+-- ============================================================================
+-- SendGrid and SES report bounces, complaints and deliveries through
+-- webhooks. Complaints (spam reports) get their own delivery status, and
+-- the provider's message id is kept in notifications.metadata so events
+-- without our notification id can still be matched.
+-- ============================================================================
+
+ALTER TYPE delivery_status ADD VALUE IF NOT EXISTS 'complained';
+
+CREATE INDEX IF NOT EXISTS idx_notifications_provider_message_id
+    ON notifications ((metadata->>'provider_message_id'))
+    WHERE metadata ? 'provider_message_id';
@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Migration: Scheduled Jobs
+-- ============================================================================
+-- Recurring jobs and their cron schedules. Instances register the jobs they
+-- can run; a row keeps its schedule and paused flag across restarts. An
+-- instance runs a due job only after taking its lease (locked_by /
+-- locked_until), so one instance runs each job at a time.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    name VARCHAR(100) PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    schedule VARCHAR(100) NOT NULL,
+    paused BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Set by `rcommerce jobs run`; runs the job once even while paused
+    run_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    locked_by VARCHAR(255),
+    locked_until TIMESTAMPTZ,
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_status VARCHAR(20) CHECK (last_status IN ('succeeded', 'failed')),
+    -- Summary of a successful run, or the error of a failed one
+    last_message TEXT,
+    last_duration_ms BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_jobs_next_run
+    ON scheduled_jobs (next_run_at)
+    WHERE NOT paused;
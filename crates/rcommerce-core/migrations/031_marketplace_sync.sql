@@ -0,0 +1,117 @@
+-- ============================================================================
+-- Migration: Marketplace Sync
+-- ============================================================================
+-- Listings map a marketplace SKU (Amazon seller SKU, eBay inventory SKU) to
+-- a product or one of its variants. Triggers stamp a listing's changed_at
+-- whenever the price or stock it mirrors changes, so every code path
+-- (API, imports, CLI, direct SQL, orders) queues a push; the sync job
+-- pushes listings changed since synced_at.
+--
+-- Imported marketplace orders are recorded once per marketplace order id,
+-- and orders carry the channel they were placed on.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'marketplace_channel') THEN
+        CREATE TYPE marketplace_channel AS ENUM ('amazon', 'ebay');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS marketplace_listings (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    channel marketplace_channel NOT NULL,
+    marketplace_sku VARCHAR(255) NOT NULL,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL lists the product itself
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    -- Pushed price = store price adjusted by this percentage (e.g. to cover fees)
+    price_adjustment_percent DECIMAL(6, 2) NOT NULL DEFAULT 0,
+    -- Units held back from the marketplace
+    stock_buffer INTEGER NOT NULL DEFAULT 0 CHECK (stock_buffer >= 0),
+    sync_price BOOLEAN NOT NULL DEFAULT TRUE,
+    sync_inventory BOOLEAN NOT NULL DEFAULT TRUE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Last change to the listing or the price/stock it mirrors
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    synced_at TIMESTAMPTZ,
+    last_synced_price DECIMAL(20, 2),
+    last_synced_quantity INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (channel, marketplace_sku)
+);
+
+CREATE INDEX IF NOT EXISTS idx_marketplace_listings_product ON marketplace_listings(product_id, variant_id);
+CREATE INDEX IF NOT EXISTS idx_marketplace_listings_pending
+    ON marketplace_listings(channel, changed_at)
+    WHERE active AND (synced_at IS NULL OR changed_at > synced_at);
+
+-- Order import progress per marketplace
+CREATE TABLE IF NOT EXISTS marketplace_sync_state (
+    channel marketplace_channel PRIMARY KEY,
+    -- Orders updated before this were imported
+    orders_synced_until TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS marketplace_orders (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    channel marketplace_channel NOT NULL,
+    marketplace_order_id VARCHAR(100) NOT NULL,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    -- Marketplace SKUs that matched no listing (the order is put on hold)
+    unmapped_skus TEXT[] NOT NULL DEFAULT ARRAY[]::TEXT[],
+    placed_at TIMESTAMPTZ NOT NULL,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (channel, marketplace_order_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_marketplace_orders_order ON marketplace_orders(order_id);
+
+-- Sales channel an order was placed on: 'web', 'amazon', 'ebay', ...
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS channel VARCHAR(20) NOT NULL DEFAULT 'web';
+CREATE INDEX IF NOT EXISTS idx_orders_channel ON orders(channel, created_at DESC);
+
+-- Queue pushes for listings of a product whose price or stock changed
+CREATE OR REPLACE FUNCTION queue_product_marketplace_sync()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.price IS DISTINCT FROM OLD.price
+        OR NEW.currency IS DISTINCT FROM OLD.currency
+        OR NEW.inventory_quantity IS DISTINCT FROM OLD.inventory_quantity
+    THEN
+        UPDATE marketplace_listings SET changed_at = NOW()
+        WHERE product_id = NEW.id AND variant_id IS NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_queue_marketplace_sync ON products;
+CREATE TRIGGER products_queue_marketplace_sync
+    AFTER UPDATE ON products
+    FOR EACH ROW
+    EXECUTE FUNCTION queue_product_marketplace_sync();
+
+-- Queue pushes for listings of a variant whose price or stock changed
+CREATE OR REPLACE FUNCTION queue_variant_marketplace_sync()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.price IS DISTINCT FROM OLD.price
+        OR NEW.currency IS DISTINCT FROM OLD.currency
+        OR NEW.inventory_quantity IS DISTINCT FROM OLD.inventory_quantity
+    THEN
+        UPDATE marketplace_listings SET changed_at = NOW()
+        WHERE variant_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS product_variants_queue_marketplace_sync ON product_variants;
+CREATE TRIGGER product_variants_queue_marketplace_sync
+    AFTER UPDATE ON product_variants
+    FOR EACH ROW
+    EXECUTE FUNCTION queue_variant_marketplace_sync();
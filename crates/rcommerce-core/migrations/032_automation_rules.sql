@@ -0,0 +1,131 @@
+-- ============================================================================
+-- Migration: Automation Rules
+-- ============================================================================
+-- Rules run actions (tag an order, hold it, notify Slack) when an order,
+-- payment or customer event matches their conditions. Triggers queue
+-- events in automation_events, but only for triggers an enabled rule
+-- listens to, so every code path that writes orders and customers feeds
+-- the engine; the automation job evaluates queued events and logs each
+-- rule that matched in automation_runs.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'automation_trigger') THEN
+        CREATE TYPE automation_trigger AS ENUM (
+            'order_created', 'order_status_changed', 'order_paid',
+            'payment_failed', 'order_refunded', 'customer_created'
+        );
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS automation_rules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    trigger automation_trigger NOT NULL,
+    -- Condition tree, e.g. {"all": [{"field": "order.total", "op": "gt", "value": 500}]}
+    conditions JSONB NOT NULL DEFAULT '{"all": []}'::JSONB,
+    -- Actions run in order when the conditions match
+    actions JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Rules for the same trigger run by ascending position
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_rules_trigger ON automation_rules(trigger, position) WHERE enabled;
+
+DROP TRIGGER IF EXISTS update_automation_rules_updated_at ON automation_rules;
+CREATE TRIGGER update_automation_rules_updated_at
+    BEFORE UPDATE ON automation_rules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS automation_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    trigger automation_trigger NOT NULL,
+    -- No foreign keys: events outlive archived orders and deleted customers
+    order_id UUID,
+    customer_id UUID,
+    -- Trigger details, e.g. old and new status
+    payload JSONB NOT NULL DEFAULT '{}'::JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_events_pending ON automation_events(created_at) WHERE processed_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS automation_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    rule_id UUID NOT NULL REFERENCES automation_rules(id) ON DELETE CASCADE,
+    event_id UUID NOT NULL,
+    trigger automation_trigger NOT NULL,
+    order_id UUID,
+    customer_id UUID,
+    -- succeeded, or failed if any action failed
+    status VARCHAR(20) NOT NULL,
+    -- Outcome of each action
+    actions JSONB NOT NULL DEFAULT '[]'::JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_runs_rule ON automation_runs(rule_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_automation_runs_order ON automation_runs(order_id) WHERE order_id IS NOT NULL;
+
+-- Queue an event if an enabled rule listens to its trigger
+CREATE OR REPLACE FUNCTION queue_automation_event(
+    p_trigger automation_trigger, p_order_id UUID, p_customer_id UUID, p_payload JSONB
+) RETURNS VOID AS $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM automation_rules WHERE enabled AND trigger = p_trigger) THEN
+        INSERT INTO automation_events (trigger, order_id, customer_id, payload)
+        VALUES (p_trigger, p_order_id, p_customer_id, p_payload);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION queue_order_automation_events() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        PERFORM queue_automation_event('order_created', NEW.id, NEW.customer_id, '{}'::JSONB);
+        -- Orders can be created paid (e.g. marketplace imports)
+        IF NEW.payment_status = 'paid' THEN
+            PERFORM queue_automation_event('order_paid', NEW.id, NEW.customer_id, '{}'::JSONB);
+        END IF;
+        RETURN NEW;
+    END IF;
+
+    IF NEW.status IS DISTINCT FROM OLD.status THEN
+        PERFORM queue_automation_event('order_status_changed', NEW.id, NEW.customer_id,
+            jsonb_build_object('old_status', OLD.status, 'new_status', NEW.status));
+    END IF;
+    IF NEW.payment_status IS DISTINCT FROM OLD.payment_status THEN
+        IF NEW.payment_status = 'paid' THEN
+            PERFORM queue_automation_event('order_paid', NEW.id, NEW.customer_id, '{}'::JSONB);
+        ELSIF NEW.payment_status = 'failed' THEN
+            PERFORM queue_automation_event('payment_failed', NEW.id, NEW.customer_id, '{}'::JSONB);
+        ELSIF NEW.payment_status = 'refunded' THEN
+            PERFORM queue_automation_event('order_refunded', NEW.id, NEW.customer_id, '{}'::JSONB);
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_orders_automation ON orders;
+CREATE TRIGGER trg_orders_automation
+    AFTER INSERT OR UPDATE OF status, payment_status ON orders
+    FOR EACH ROW EXECUTE FUNCTION queue_order_automation_events();
+
+CREATE OR REPLACE FUNCTION queue_customer_automation_events() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM queue_automation_event('customer_created', NULL, NEW.id, '{}'::JSONB);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_customers_automation ON customers;
+CREATE TRIGGER trg_customers_automation
+    AFTER INSERT ON customers
+    FOR EACH ROW EXECUTE FUNCTION queue_customer_automation_events();
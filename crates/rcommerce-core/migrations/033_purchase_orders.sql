@@ -0,0 +1,114 @@
+-- ============================================================================
+-- Migration: Suppliers and Purchase Orders
+-- ============================================================================
+-- Suppliers sell products (supplier_products, with a preferred supplier
+-- per product). Purchase orders bring stock into a location: placing one
+-- adds its quantities to incoming_quantity, and receiving moves them from
+-- incoming_quantity to available_quantity with an 'in' stock movement.
+-- The reorder job alerts staff about levels at their reorder_point and
+-- can draft purchase orders for reorder_quantity from the preferred
+-- supplier.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS suppliers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    code VARCHAR(50) UNIQUE,
+    contact_name VARCHAR(255),
+    email VARCHAR(255),
+    phone VARCHAR(50),
+    address TEXT,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    -- Days from ordering to delivery, for expected dates of drafted orders
+    lead_time_days INTEGER NOT NULL DEFAULT 7 CHECK (lead_time_days >= 0),
+    notes TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_suppliers_updated_at ON suppliers;
+CREATE TRIGGER update_suppliers_updated_at
+    BEFORE UPDATE ON suppliers
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Products a supplier sells, and at what cost
+CREATE TABLE IF NOT EXISTS supplier_products (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    supplier_id UUID NOT NULL REFERENCES suppliers(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    supplier_sku VARCHAR(100),
+    unit_cost DECIMAL(10,2),
+    -- Drafted purchase orders go to the preferred supplier
+    is_preferred BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_supplier_products_unique
+    ON supplier_products(supplier_id, product_id, COALESCE(variant_id, '00000000-0000-0000-0000-000000000000'::UUID));
+CREATE UNIQUE INDEX IF NOT EXISTS idx_supplier_products_preferred
+    ON supplier_products(product_id, COALESCE(variant_id, '00000000-0000-0000-0000-000000000000'::UUID))
+    WHERE is_preferred;
+CREATE INDEX IF NOT EXISTS idx_supplier_products_product ON supplier_products(product_id);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'purchase_order_status') THEN
+        CREATE TYPE purchase_order_status AS ENUM (
+            'draft', 'ordered', 'partially_received', 'received', 'cancelled'
+        );
+    END IF;
+END$$;
+
+CREATE SEQUENCE IF NOT EXISTS purchase_order_number_seq START 1001;
+
+CREATE TABLE IF NOT EXISTS purchase_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    po_number VARCHAR(50) NOT NULL UNIQUE
+        DEFAULT 'PO-' || nextval('purchase_order_number_seq')::TEXT,
+    supplier_id UUID NOT NULL REFERENCES suppliers(id) ON DELETE RESTRICT,
+    -- Where the stock is delivered
+    location_id UUID NOT NULL REFERENCES inventory_locations(id) ON DELETE RESTRICT,
+    status purchase_order_status NOT NULL DEFAULT 'draft',
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    expected_at TIMESTAMPTZ,
+    notes TEXT,
+    -- Drafted by the reorder job rather than by staff
+    auto_drafted BOOLEAN NOT NULL DEFAULT false,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    ordered_at TIMESTAMPTZ,
+    received_at TIMESTAMPTZ,
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_status ON purchase_orders(status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_supplier ON purchase_orders(supplier_id);
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_expected ON purchase_orders(expected_at)
+    WHERE status IN ('ordered', 'partially_received');
+
+DROP TRIGGER IF EXISTS update_purchase_orders_updated_at ON purchase_orders;
+CREATE TRIGGER update_purchase_orders_updated_at
+    BEFORE UPDATE ON purchase_orders
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS purchase_order_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    purchase_order_id UUID NOT NULL REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE RESTRICT,
+    supplier_sku VARCHAR(100),
+    quantity_ordered INTEGER NOT NULL CHECK (quantity_ordered > 0),
+    quantity_received INTEGER NOT NULL DEFAULT 0
+        CHECK (quantity_received >= 0 AND quantity_received <= quantity_ordered),
+    unit_cost DECIMAL(10,2),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_items_order ON purchase_order_items(purchase_order_id);
+CREATE INDEX IF NOT EXISTS idx_purchase_order_items_product ON purchase_order_items(product_id, variant_id);
+
+-- Levels staff were alerted about; cleared once stock is back above the reorder point
+ALTER TABLE inventory_levels ADD COLUMN IF NOT EXISTS low_stock_alerted_at TIMESTAMPTZ;
@@ -0,0 +1,83 @@
+-- ============================================================================
+-- Migration: Report Subscriptions
+-- ============================================================================
+-- Stakeholders subscribe to a report (sales summary, tax liability, low
+-- stock, failed payments) on a daily, weekly or monthly schedule. The
+-- reports job generates due reports as CSV, XLSX or PDF, stores them in
+-- report_deliveries and emails the recipients a download link through the
+-- notification queue. Deliveries are the report history and can be sent
+-- again while their file is kept.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'report_kind') THEN
+        CREATE TYPE report_kind AS ENUM (
+            'sales_summary', 'tax_liability', 'low_stock', 'failed_payments'
+        );
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'report_schedule') THEN
+        CREATE TYPE report_schedule AS ENUM ('daily', 'weekly', 'monthly');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'report_format') THEN
+        CREATE TYPE report_format AS ENUM ('csv', 'xlsx', 'pdf');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'report_delivery_status') THEN
+        CREATE TYPE report_delivery_status AS ENUM ('sent', 'failed');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS report_subscriptions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL,
+    report report_kind NOT NULL,
+    schedule report_schedule NOT NULL,
+    format report_format NOT NULL DEFAULT 'csv',
+    recipients TEXT[] NOT NULL,
+    -- When the report is sent (UTC): the hour, the ISO weekday (1 = Monday)
+    -- for weekly reports and the day of the month for monthly ones
+    hour SMALLINT NOT NULL DEFAULT 6 CHECK (hour BETWEEN 0 AND 23),
+    weekday SMALLINT NOT NULL DEFAULT 1 CHECK (weekday BETWEEN 1 AND 7),
+    day_of_month SMALLINT NOT NULL DEFAULT 1 CHECK (day_of_month BETWEEN 1 AND 28),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_subscriptions_due ON report_subscriptions(next_run_at) WHERE is_active;
+
+DROP TRIGGER IF EXISTS update_report_subscriptions_updated_at ON report_subscriptions;
+CREATE TRIGGER update_report_subscriptions_updated_at
+    BEFORE UPDATE ON report_subscriptions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS report_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    -- Kept when the subscription is deleted, as history
+    subscription_id UUID REFERENCES report_subscriptions(id) ON DELETE SET NULL,
+    report report_kind NOT NULL,
+    format report_format NOT NULL,
+    -- The period covered; NULL for snapshots such as low stock
+    period_start TIMESTAMPTZ,
+    period_end TIMESTAMPTZ,
+    recipients TEXT[] NOT NULL,
+    status report_delivery_status NOT NULL,
+    error TEXT,
+    filename VARCHAR(255) NOT NULL,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    -- Cleared once the download link expires
+    content BYTEA,
+    size_bytes INTEGER NOT NULL DEFAULT 0,
+    download_token VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Times the report was emailed, counting re-sends
+    sent_count INTEGER NOT NULL DEFAULT 0,
+    last_sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_deliveries_subscription ON report_deliveries(subscription_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_report_deliveries_expires ON report_deliveries(expires_at) WHERE content IS NOT NULL;
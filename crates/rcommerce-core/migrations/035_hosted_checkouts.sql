@@ -0,0 +1,51 @@
+-- ============================================================================
+-- Migration: Hosted Checkouts
+-- ============================================================================
+-- Hosted payment pages created from an order: Stripe Checkout Sessions that
+-- the storefront redirects to, and Payment Links that staff send to
+-- customers. The gateway's checkout webhooks mark the order paid (and
+-- confirmed) and record the payment; the page is then closed along with any
+-- other page still open for the order.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'hosted_checkout_mode') THEN
+        CREATE TYPE hosted_checkout_mode AS ENUM ('checkout_session', 'payment_link');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'hosted_checkout_status') THEN
+        CREATE TYPE hosted_checkout_status AS ENUM ('open', 'completed', 'failed', 'expired');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS hosted_checkouts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    gateway VARCHAR(50) NOT NULL,
+    mode hosted_checkout_mode NOT NULL,
+    -- Checkout session or payment link ID at the gateway
+    provider_id VARCHAR(255) NOT NULL,
+    url TEXT NOT NULL,
+    amount DECIMAL(20, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    status hosted_checkout_status NOT NULL DEFAULT 'open',
+    success_url TEXT NOT NULL,
+    cancel_url TEXT,
+    -- Gateway payment that completed the checkout
+    payment_id VARCHAR(255),
+    error TEXT,
+    -- Checkout sessions expire; payment links stay open until paid or expired here
+    expires_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (gateway, provider_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_hosted_checkouts_order ON hosted_checkouts(order_id, created_at DESC);
+
+DROP TRIGGER IF EXISTS update_hosted_checkouts_updated_at ON hosted_checkouts;
+CREATE TRIGGER update_hosted_checkouts_updated_at
+    BEFORE UPDATE ON hosted_checkouts
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,23 @@
+-- ============================================================================
+-- Migration: Shipping Rules
+-- ============================================================================
+-- Conditional shipping rules (free shipping thresholds, surcharges, hidden
+-- methods) stored per instance so they are part of the catalog that is
+-- diffed between staging and production and promoted with
+-- `rcommerce promote`. `condition` and `action` hold the serialized
+-- `RuleCondition` and `RuleAction` of a `ShippingRule`.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS shipping_rules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    condition JSONB NOT NULL,
+    action JSONB NOT NULL,
+    -- Higher is applied first
+    priority INTEGER NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipping_rules_enabled ON shipping_rules(enabled, priority DESC);
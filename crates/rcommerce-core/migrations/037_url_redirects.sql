@@ -0,0 +1,97 @@
+-- ============================================================================
+-- Migration: Slug History and URL Redirects
+-- ============================================================================
+-- Keeps the slugs products and categories had before, so links to an old
+-- URL can be redirected to the current one, and manual redirects managed
+-- by staff (e.g. for pages moved off a previous platform).
+--
+-- Slug history rows are written by triggers, so every code path that
+-- changes a slug (API, imports, catalog promotion, direct SQL) is recorded.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'slug_entity_type') THEN
+        CREATE TYPE slug_entity_type AS ENUM ('product', 'category');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS slug_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    entity_type slug_entity_type NOT NULL,
+    entity_id UUID NOT NULL,
+    slug VARCHAR(255) NOT NULL,
+    -- When the entity stopped using the slug
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- An old slug redirects to the entity that used it last
+    UNIQUE (entity_type, slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_slug_history_entity ON slug_history(entity_type, entity_id, changed_at DESC);
+
+-- Record the old slug when it changes; a slug taken into use again is live,
+-- so it no longer redirects
+CREATE OR REPLACE FUNCTION record_slug_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    kind slug_entity_type := TG_ARGV[0]::slug_entity_type;
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.slug IS DISTINCT FROM OLD.slug THEN
+        INSERT INTO slug_history (entity_type, entity_id, slug)
+        VALUES (kind, OLD.id, OLD.slug)
+        ON CONFLICT (entity_type, slug) DO UPDATE
+        SET entity_id = EXCLUDED.entity_id, changed_at = NOW();
+    END IF;
+    IF TG_OP = 'INSERT' OR NEW.slug IS DISTINCT FROM OLD.slug THEN
+        DELETE FROM slug_history WHERE entity_type = kind AND slug = NEW.slug;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_record_slug ON products;
+CREATE TRIGGER products_record_slug
+    AFTER INSERT OR UPDATE OF slug ON products
+    FOR EACH ROW
+    EXECUTE FUNCTION record_slug_change('product');
+
+DROP TRIGGER IF EXISTS product_categories_record_slug ON product_categories;
+CREATE TRIGGER product_categories_record_slug
+    AFTER INSERT OR UPDATE OF slug ON product_categories
+    FOR EACH ROW
+    EXECUTE FUNCTION record_slug_change('category');
+
+-- History of deleted entities goes with them
+CREATE OR REPLACE FUNCTION delete_slug_history()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM slug_history WHERE entity_type = TG_ARGV[0]::slug_entity_type AND entity_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_delete_slug_history ON products;
+CREATE TRIGGER products_delete_slug_history
+    AFTER DELETE ON products
+    FOR EACH ROW
+    EXECUTE FUNCTION delete_slug_history('product');
+
+DROP TRIGGER IF EXISTS product_categories_delete_slug_history ON product_categories;
+CREATE TRIGGER product_categories_delete_slug_history
+    AFTER DELETE ON product_categories
+    FOR EACH ROW
+    EXECUTE FUNCTION delete_slug_history('category');
+
+-- Manual redirects, matched on the exact path
+CREATE TABLE IF NOT EXISTS url_redirects (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    source_path VARCHAR(2048) NOT NULL UNIQUE,
+    -- A path on the storefront or an absolute URL
+    target_path VARCHAR(2048) NOT NULL,
+    status_code SMALLINT NOT NULL DEFAULT 301 CHECK (status_code IN (301, 302, 307, 308)),
+    hits BIGINT NOT NULL DEFAULT 0,
+    last_hit_at TIMESTAMPTZ,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
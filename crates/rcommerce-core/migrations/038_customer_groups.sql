@@ -0,0 +1,56 @@
+-- ============================================================================
+-- Migration: Customer Groups
+-- ============================================================================
+-- Customers can belong to one group (wholesale, VIP, retail, ...). A group
+-- takes a percentage off every price, and can override the price of single
+-- products or variants, optionally in quantity tiers (the price for the
+-- highest `min_quantity` not above the quantity bought applies). Override
+-- prices are in the product's own currency, like its base price.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS customer_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    -- Taken off prices the group has no override for
+    discount_percent DECIMAL(5, 2) NOT NULL DEFAULT 0
+        CHECK (discount_percent >= 0 AND discount_percent <= 100),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_customer_groups_updated_at ON customer_groups;
+CREATE TRIGGER update_customer_groups_updated_at
+    BEFORE UPDATE ON customer_groups
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE customers
+    ADD COLUMN IF NOT EXISTS customer_group_id UUID REFERENCES customer_groups(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_customers_customer_group ON customers(customer_group_id)
+    WHERE customer_group_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS customer_group_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_group_id UUID NOT NULL REFERENCES customer_groups(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL prices the product and all of its variants
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    -- Smallest quantity the price applies to
+    min_quantity INTEGER NOT NULL DEFAULT 1 CHECK (min_quantity >= 1),
+    price DECIMAL(20, 2) NOT NULL CHECK (price >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One price per product (or variant) and tier per group
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customer_group_prices_product
+    ON customer_group_prices(customer_group_id, product_id, min_quantity) WHERE variant_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customer_group_prices_variant
+    ON customer_group_prices(customer_group_id, product_id, variant_id, min_quantity) WHERE variant_id IS NOT NULL;
+
+DROP TRIGGER IF EXISTS update_customer_group_prices_updated_at ON customer_group_prices;
+CREATE TRIGGER update_customer_group_prices_updated_at
+    BEFORE UPDATE ON customer_group_prices
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
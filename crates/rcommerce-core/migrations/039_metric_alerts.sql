@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Migration: Business Metrics and Alerts
+-- ============================================================================
+-- Business metrics (orders per minute, payment failure rate, the checkout
+-- funnel) are counted over a recent window of orders, payments and carts;
+-- the indexes below keep those counts cheap.
+--
+-- Alert rules live in `[observability.alerts]`. Their state is kept here
+-- so that whichever instance runs the `metric_alerts` job knows how long a
+-- rule has been breached and whether staff were already told.
+-- ============================================================================
+
+CREATE INDEX IF NOT EXISTS idx_payments_updated_at ON payments(updated_at);
+CREATE INDEX IF NOT EXISTS idx_carts_created_at ON carts(created_at);
+
+CREATE TABLE IF NOT EXISTS metric_alert_states (
+    -- The rule's name in `[[observability.alerts.rules]]`
+    rule_name VARCHAR(100) PRIMARY KEY,
+    -- When the current breach began; NULL while within the threshold
+    breached_since TIMESTAMPTZ,
+    -- Staff were told about the current breach
+    firing BOOLEAN NOT NULL DEFAULT FALSE,
+    last_value DOUBLE PRECISION,
+    last_notified_at TIMESTAMPTZ,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,58 @@
+-- ============================================================================
+-- Migration: Anomaly Detection and Incidents
+-- ============================================================================
+-- The `anomaly_detection` job compares the payment failure, refund and 5xx
+-- rates of the latest window with their recent history (a rolling z-score)
+-- and opens an incident when one spikes, e.g. after a gateway
+-- misconfiguration. Incidents resolve by themselves once the rate is back
+-- to normal, or are acknowledged and resolved by staff.
+--
+-- Each API instance adds its request and 5xx counts to `request_stats`
+-- once a minute, so 5xx rates cover every instance.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS request_stats (
+    minute TIMESTAMPTZ PRIMARY KEY,
+    requests BIGINT NOT NULL DEFAULT 0,
+    server_errors BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_created_at ON refunds(created_at);
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'anomaly_metric') THEN
+        CREATE TYPE anomaly_metric AS ENUM ('payment_failure_rate', 'refund_rate', 'server_error_rate');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'incident_status') THEN
+        CREATE TYPE incident_status AS ENUM ('open', 'acknowledged', 'resolved');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS incidents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    metric anomaly_metric NOT NULL,
+    status incident_status NOT NULL DEFAULT 'open',
+    summary TEXT NOT NULL,
+    -- The rate (a percentage) and the baseline it was compared with
+    rate DOUBLE PRECISION NOT NULL,
+    baseline_mean DOUBLE PRECISION NOT NULL,
+    baseline_stddev DOUBLE PRECISION NOT NULL,
+    z_score DOUBLE PRECISION NOT NULL,
+    peak_rate DOUBLE PRECISION NOT NULL,
+    peak_z_score DOUBLE PRECISION NOT NULL,
+    -- Events the latest rate was measured from
+    samples BIGINT NOT NULL,
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMPTZ,
+    acknowledged_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    -- NULL when the rate went back to normal by itself
+    resolved_by UUID REFERENCES customers(id) ON DELETE SET NULL
+);
+
+-- At most one unresolved incident per metric
+CREATE UNIQUE INDEX IF NOT EXISTS idx_incidents_unresolved ON incidents(metric)
+    WHERE status <> 'resolved';
+CREATE INDEX IF NOT EXISTS idx_incidents_opened_at ON incidents(opened_at DESC);
@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Migration: Categories and Collections
+-- ============================================================================
+-- Categories (`product_categories`) form a tree; listing a category's
+-- products includes its subcategories. Collections are either manual (the
+-- products in `collection_products`, in their `position` order) or smart:
+-- every product matching the collection's rules on tag, price or vendor
+-- (all of them, or any one when `disjunctive`).
+-- ============================================================================
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS vendor VARCHAR(255);
+CREATE INDEX IF NOT EXISTS idx_products_vendor ON products(LOWER(vendor)) WHERE vendor IS NOT NULL;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'collection_type') THEN
+        CREATE TYPE collection_type AS ENUM ('manual', 'smart');
+    END IF;
+END$$;
+
+ALTER TABLE collections ADD COLUMN IF NOT EXISTS collection_type collection_type NOT NULL DEFAULT 'manual';
+-- [{"field": "tag", "relation": "equals", "value": "summer"}, ...]
+ALTER TABLE collections ADD COLUMN IF NOT EXISTS rules JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX IF NOT EXISTS idx_product_tags_name ON product_tags(LOWER(name));
+CREATE INDEX IF NOT EXISTS idx_collection_products_position ON collection_products(collection_id, position);
@@ -0,0 +1,22 @@
+-- ============================================================================
+-- Migration: Product Media
+-- ============================================================================
+-- Product images uploaded through the API or downloaded by importers are
+-- kept in the configured storage (local disk or an S3-compatible bucket)
+-- under `storage_key`, with their resized variants (thumbnail, small, ...)
+-- in `variants`. Images linked before this migration have no storage key.
+--
+-- `source_url` is where an imported image was downloaded from, so imports
+-- run again do not download it twice.
+-- ============================================================================
+
+ALTER TABLE product_images
+    ADD COLUMN IF NOT EXISTS storage_key VARCHAR(500),
+    ADD COLUMN IF NOT EXISTS content_type VARCHAR(100),
+    ADD COLUMN IF NOT EXISTS file_size BIGINT,
+    ADD COLUMN IF NOT EXISTS variants JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN IF NOT EXISTS source_url TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_product_images_position ON product_images(product_id, position);
+CREATE INDEX IF NOT EXISTS idx_product_images_source_url ON product_images(product_id, source_url)
+    WHERE source_url IS NOT NULL;
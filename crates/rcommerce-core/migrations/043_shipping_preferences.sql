@@ -0,0 +1,41 @@
+-- ============================================================================
+-- Migration: Shipping Preferences and Backorders
+-- ============================================================================
+-- Orders ship complete (held until every item is in stock) or partial (what
+-- is in stock ships now). The order's `shipping_preference` wins over its
+-- customer's; NULL falls back to `fulfillment.default_shipping_preference`.
+--
+-- Fulfillments now record the location they ship from, and whether they
+-- ship backordered items. `order_backorders` holds the units of each order
+-- line waiting for stock; rows are removed as the units ship.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'shipping_preference') THEN
+        CREATE TYPE shipping_preference AS ENUM ('ship_complete', 'ship_partial');
+    END IF;
+END$$;
+
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS shipping_preference shipping_preference;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS shipping_preference shipping_preference;
+
+ALTER TABLE fulfillments
+    ADD COLUMN IF NOT EXISTS location_id UUID REFERENCES inventory_locations(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS is_backorder BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS order_backorders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(order_item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_backorders_order ON order_backorders(order_id);
+CREATE INDEX IF NOT EXISTS idx_order_backorders_product ON order_backorders(product_id, variant_id);
+CREATE INDEX IF NOT EXISTS idx_fulfillments_location ON fulfillments(location_id) WHERE location_id IS NOT NULL;
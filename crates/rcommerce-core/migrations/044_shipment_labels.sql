@@ -0,0 +1,12 @@
+-- ============================================================================
+-- Migration: Shipment Labels
+-- ============================================================================
+-- Shipping labels are bought on the carrier account of the location a
+-- shipment leaves from (`[[shipping.locations]]`). Fulfillments keep the
+-- carrier service and label of their parcel; the carrier goes in
+-- `tracking_company`.
+-- ============================================================================
+
+ALTER TABLE fulfillments
+    ADD COLUMN IF NOT EXISTS service_code VARCHAR(100),
+    ADD COLUMN IF NOT EXISTS label_url TEXT;
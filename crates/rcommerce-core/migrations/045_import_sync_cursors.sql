@@ -0,0 +1,17 @@
+-- ============================================================================
+-- Migration: Import Sync Cursors
+-- ============================================================================
+-- When each platform import last completed without errors, per store (its
+-- API URL) and entity type. `rcommerce import platform --incremental` only
+-- fetches records changed since then and updates the ones that exist.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS import_sync_cursors (
+    platform VARCHAR(50) NOT NULL,
+    source TEXT NOT NULL,
+    entity VARCHAR(50) NOT NULL,
+    synced_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (platform, source, entity)
+);
@@ -0,0 +1,32 @@
+-- ============================================================================
+-- Migration: Landed Cost
+-- ============================================================================
+-- Cross-border orders record their incoterm: DDP when the customer prepaid
+-- duties and import taxes at checkout (`duty_total`, `import_tax_total`,
+-- included in the order total), DAP when they pay on delivery. Domestic
+-- orders have none.
+--
+-- `product_customs` holds what customs documents and the duty estimator
+-- need per product: its HS code and country of origin.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'incoterm') THEN
+        CREATE TYPE incoterm AS ENUM ('ddp', 'dap');
+    END IF;
+END$$;
+
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS incoterm incoterm,
+    ADD COLUMN IF NOT EXISTS duty_total DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS import_tax_total DECIMAL(20, 2) NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS product_customs (
+    product_id UUID PRIMARY KEY REFERENCES products(id) ON DELETE CASCADE,
+    hs_code VARCHAR(20),
+    origin_country VARCHAR(2),
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
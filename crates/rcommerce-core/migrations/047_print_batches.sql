@@ -0,0 +1,50 @@
+-- ============================================================================
+-- Migration: Print Batches
+-- ============================================================================
+-- A print batch merges the shipping labels or packing slips of many
+-- shipments into one PDF for a printer profile (`[printing]`). Batches are
+-- generated, then marked printed or failed by the print station. Printing
+-- a batch of labels stamps `label_printed_at` on its shipments, so the next
+-- batch of unprinted labels leaves them out.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'print_batch_status') THEN
+        CREATE TYPE print_batch_status AS ENUM ('generated', 'printed', 'failed');
+    END IF;
+END$$;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'print_document_kind') THEN
+        CREATE TYPE print_document_kind AS ENUM ('shipping_label', 'packing_slip');
+    END IF;
+END$$;
+
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS label_printed_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS print_batches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    document_kind print_document_kind NOT NULL,
+    profile VARCHAR(100) NOT NULL,
+    status print_batch_status NOT NULL DEFAULT 'generated',
+    document_count INTEGER NOT NULL,
+    page_count INTEGER NOT NULL,
+    pdf BYTEA NOT NULL,
+    error TEXT,
+    created_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    printed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS print_batch_items (
+    batch_id UUID NOT NULL REFERENCES print_batches(id) ON DELETE CASCADE,
+    shipment_id UUID NOT NULL REFERENCES fulfillments(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (batch_id, shipment_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_print_batches_status ON print_batches(status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_print_batch_items_shipment ON print_batch_items(shipment_id);
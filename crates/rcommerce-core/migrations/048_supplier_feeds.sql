@@ -0,0 +1,105 @@
+-- ============================================================================
+-- Migration: Supplier Feeds and Dropship Orders
+-- ============================================================================
+-- A supplier's stock and price file (CSV, XML or JSON) is pulled from
+-- source_url by the supplier_feeds job or pushed to the feed's webhook.
+-- Rows are matched to supplier_products by supplier SKU: the supplier's
+-- stock and cost are recorded, dropshipped products take the supplier's
+-- stock as their own, prices can follow the cost through margin rules,
+-- and products are disabled while the supplier is out of stock (and
+-- enabled again once restocked, if the feed disabled them).
+--
+-- With dropship, paid order lines of the supplier's products are routed
+-- to it: the dropship job groups them into one dropship order per order
+-- and supplier and sends it to order_webhook_url, or emails the supplier.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'supplier_feed_format') THEN
+        CREATE TYPE supplier_feed_format AS ENUM ('csv', 'xml', 'json');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'dropship_order_status') THEN
+        CREATE TYPE dropship_order_status AS ENUM ('pending', 'sent', 'failed');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS supplier_feeds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    supplier_id UUID NOT NULL UNIQUE REFERENCES suppliers(id) ON DELETE CASCADE,
+    format supplier_feed_format NOT NULL,
+    -- Pulled by the supplier_feeds job; NULL for feeds that are only pushed
+    source_url TEXT,
+    pull_interval_minutes INTEGER NOT NULL DEFAULT 60 CHECK (pull_interval_minutes >= 5),
+    -- Key of the HMAC-SHA256 signature of pushed files and sent orders
+    webhook_secret VARCHAR(64) NOT NULL,
+    -- Field names of the SKU, stock and cost (FeedMapping)
+    mapping JSONB NOT NULL DEFAULT '{}'::JSONB,
+    -- Selling prices from costs (MarginRule list, first match wins)
+    margin_rules JSONB NOT NULL DEFAULT '[]'::JSONB,
+    update_prices BOOLEAN NOT NULL DEFAULT false,
+    -- Disable products while the supplier has none in stock
+    auto_disable BOOLEAN NOT NULL DEFAULT true,
+    -- Route paid orders of the supplier's products to it
+    dropship BOOLEAN NOT NULL DEFAULT false,
+    -- Dropship orders are POSTed here as JSON; without it they are emailed
+    order_webhook_url TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    last_synced_at TIMESTAMPTZ,
+    last_error TEXT,
+    last_report JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_supplier_feeds_due ON supplier_feeds(last_synced_at)
+    WHERE is_active AND source_url IS NOT NULL;
+
+DROP TRIGGER IF EXISTS update_supplier_feeds_updated_at ON supplier_feeds;
+CREATE TRIGGER update_supplier_feeds_updated_at
+    BEFORE UPDATE ON supplier_feeds
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Supplier stock from the latest feed
+ALTER TABLE supplier_products ADD COLUMN IF NOT EXISTS supplier_stock INTEGER;
+ALTER TABLE supplier_products ADD COLUMN IF NOT EXISTS stock_synced_at TIMESTAMPTZ;
+-- The feed disabled the product (or variant), so restocking enables it again
+ALTER TABLE supplier_products ADD COLUMN IF NOT EXISTS disabled_by_feed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS idx_supplier_products_sku ON supplier_products(supplier_id, supplier_sku);
+
+CREATE TABLE IF NOT EXISTS dropship_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    supplier_id UUID NOT NULL REFERENCES suppliers(id) ON DELETE RESTRICT,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    status dropship_order_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- The supplier's order number, when its webhook returns one
+    supplier_reference VARCHAR(100),
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (order_id, supplier_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dropship_orders_status ON dropship_orders(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_dropship_orders_supplier ON dropship_orders(supplier_id, created_at DESC);
+
+DROP TRIGGER IF EXISTS update_dropship_orders_updated_at ON dropship_orders;
+CREATE TRIGGER update_dropship_orders_updated_at
+    BEFORE UPDATE ON dropship_orders
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS dropship_order_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    dropship_order_id UUID NOT NULL REFERENCES dropship_orders(id) ON DELETE CASCADE,
+    -- Each order line is routed once
+    order_item_id UUID NOT NULL UNIQUE REFERENCES order_items(id) ON DELETE CASCADE,
+    supplier_sku VARCHAR(100),
+    title VARCHAR(255) NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_cost DECIMAL(10,2)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dropship_order_items_order ON dropship_order_items(dropship_order_id);
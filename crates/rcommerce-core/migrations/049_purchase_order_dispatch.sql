@@ -0,0 +1,120 @@
+-- ============================================================================
+-- Migration: Purchase Order Dispatch, Acknowledgments and Receipt Matching
+-- ============================================================================
+-- Placing a purchase order queues it for dispatch to the supplier: the
+-- po_dispatch job emails it, or uploads it as an EDI-lite CSV over SFTP
+-- (supplier_po_dispatch; suppliers without settings are emailed). The
+-- supplier acknowledges it through the link in the email (ack_token) or
+-- staff record the acknowledgment, confirming quantities, costs and an
+-- ETA. Confirmed quantities and costs that differ from the order, and
+-- deliveries whose invoiced quantities or costs differ from what was
+-- received or ordered, are recorded as discrepancies for the purchasing
+-- team. Every change of a placed order's expected date is kept as an
+-- eta_changed event.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'po_dispatch_method') THEN
+        CREATE TYPE po_dispatch_method AS ENUM ('email', 'sftp', 'manual');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'po_dispatch_status') THEN
+        CREATE TYPE po_dispatch_status AS ENUM ('pending', 'sent', 'failed');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'po_event_type') THEN
+        CREATE TYPE po_event_type AS ENUM (
+            'dispatched', 'dispatch_failed', 'acknowledged', 'eta_changed', 'discrepancy', 'ack_overdue', 'overdue'
+        );
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'po_discrepancy_kind') THEN
+        CREATE TYPE po_discrepancy_kind AS ENUM ('quantity', 'price');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'po_discrepancy_source') THEN
+        CREATE TYPE po_discrepancy_source AS ENUM ('acknowledgment', 'receipt');
+    END IF;
+END$$;
+
+-- How purchase orders reach a supplier
+CREATE TABLE IF NOT EXISTS supplier_po_dispatch (
+    supplier_id UUID PRIMARY KEY REFERENCES suppliers(id) ON DELETE CASCADE,
+    method po_dispatch_method NOT NULL DEFAULT 'email',
+    -- Where emailed orders go; defaults to the supplier's email
+    email VARCHAR(255),
+    -- user@host:/directory the EDI-lite CSV is uploaded to
+    sftp_target TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_supplier_po_dispatch_updated_at ON supplier_po_dispatch;
+CREATE TRIGGER update_supplier_po_dispatch_updated_at
+    BEFORE UPDATE ON supplier_po_dispatch
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- NULL for orders placed before dispatch existed, or not placed yet
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS dispatch_status po_dispatch_status;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS dispatch_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS dispatch_error TEXT;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS dispatched_at TIMESTAMPTZ;
+-- Lets the supplier acknowledge the order without an account
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS ack_token VARCHAR(64) UNIQUE;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS acknowledged_at TIMESTAMPTZ;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS supplier_reference VARCHAR(100);
+-- Staff were alerted that the order is unacknowledged, or late
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS ack_alerted_at TIMESTAMPTZ;
+ALTER TABLE purchase_orders ADD COLUMN IF NOT EXISTS overdue_alerted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_dispatch ON purchase_orders(dispatch_status)
+    WHERE dispatch_status IN ('pending', 'failed');
+
+-- What the supplier confirmed
+ALTER TABLE purchase_order_items ADD COLUMN IF NOT EXISTS confirmed_quantity INTEGER CHECK (confirmed_quantity >= 0);
+ALTER TABLE purchase_order_items ADD COLUMN IF NOT EXISTS confirmed_unit_cost DECIMAL(10,2);
+
+CREATE TABLE IF NOT EXISTS purchase_order_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    purchase_order_id UUID NOT NULL REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    event po_event_type NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}'::JSONB,
+    actor_id UUID REFERENCES customers(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_events_order ON purchase_order_events(purchase_order_id, created_at);
+
+CREATE TABLE IF NOT EXISTS purchase_order_discrepancies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    purchase_order_id UUID NOT NULL REFERENCES purchase_orders(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES purchase_order_items(id) ON DELETE CASCADE,
+    source po_discrepancy_source NOT NULL,
+    kind po_discrepancy_kind NOT NULL,
+    -- Units for quantities, unit costs for prices
+    expected DECIMAL(12,2) NOT NULL,
+    actual DECIMAL(12,2) NOT NULL,
+    resolved_at TIMESTAMPTZ,
+    resolved_by UUID REFERENCES customers(id) ON DELETE SET NULL,
+    resolution TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_order_discrepancies_open ON purchase_order_discrepancies(created_at)
+    WHERE resolved_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_purchase_order_discrepancies_order ON purchase_order_discrepancies(purchase_order_id);
+
+-- Keep the ETA history of placed orders; a new ETA can be late again
+CREATE OR REPLACE FUNCTION record_purchase_order_eta_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.expected_at IS DISTINCT FROM OLD.expected_at
+       AND NEW.status IN ('ordered', 'partially_received') THEN
+        INSERT INTO purchase_order_events (purchase_order_id, event, details)
+        VALUES (NEW.id, 'eta_changed', jsonb_build_object('from', OLD.expected_at, 'to', NEW.expected_at));
+        NEW.overdue_alerted_at := NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS record_purchase_order_eta_change ON purchase_orders;
+CREATE TRIGGER record_purchase_order_eta_change
+    BEFORE UPDATE OF expected_at ON purchase_orders
+    FOR EACH ROW EXECUTE FUNCTION record_purchase_order_eta_change();
@@ -0,0 +1,50 @@
+-- ============================================================================
+-- Migration: VAT Validation History and Re-verification Queue
+-- ============================================================================
+-- Every VIES check is recorded in vat_validations, including checks that
+-- couldn't reach VIES. When VIES is unavailable at checkout the VAT ID is
+-- accepted provisionally and queued in vat_reverifications; the
+-- vat_reverification job retries it with backoff until VIES answers or the
+-- attempts run out. vat_id_validations stays the cache of the latest
+-- answer VIES gave.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'vat_validation_status') THEN
+        CREATE TYPE vat_validation_status AS ENUM ('valid', 'invalid', 'unavailable');
+    END IF;
+END$$;
+
+-- Outcome of each VIES check
+CREATE TABLE IF NOT EXISTS vat_validations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vat_id VARCHAR(50) NOT NULL,
+    country_code CHAR(2) NOT NULL,
+    status vat_validation_status NOT NULL,
+    business_name VARCHAR(255),
+    business_address TEXT,
+    error_message TEXT,
+    -- 'checkout' or 'reverification'
+    source VARCHAR(20) NOT NULL DEFAULT 'checkout',
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vat_validations_vat_id ON vat_validations(vat_id, checked_at DESC);
+
+-- VAT IDs accepted while VIES was unavailable, waiting to be checked again
+CREATE TABLE IF NOT EXISTS vat_reverifications (
+    vat_id VARCHAR(50) PRIMARY KEY,
+    country_code CHAR(2) NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set once VIES answered or the attempts ran out
+    resolved_at TIMESTAMPTZ,
+    -- The answer VIES finally gave; NULL when the attempts ran out
+    is_valid BOOLEAN
+);
+
+CREATE INDEX IF NOT EXISTS idx_vat_reverifications_due ON vat_reverifications(next_attempt_at)
+    WHERE resolved_at IS NULL;
@@ -0,0 +1,38 @@
+-- ============================================================================
+-- Migration: Storefront Funnel Events
+-- ============================================================================
+-- First-party analytics: storefronts post product_viewed, added_to_cart,
+-- checkout_started and payment_submitted events, which the checkout funnel
+-- (admin endpoint and checkout_funnel report) counts per session. Session
+-- IDs are stored as salted hashes; no IP address or user agent is kept.
+-- weight is 1 / the sample rate the event was kept at, so funnel counts
+-- stay comparable when the sample rate changes.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'storefront_event_kind') THEN
+        CREATE TYPE storefront_event_kind AS ENUM (
+            'product_viewed', 'added_to_cart', 'checkout_started', 'payment_submitted'
+        );
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS storefront_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event storefront_event_kind NOT NULL,
+    session_hash CHAR(64) NOT NULL,
+    product_id UUID,
+    variant_id UUID,
+    cart_id UUID,
+    quantity INT,
+    weight DOUBLE PRECISION NOT NULL DEFAULT 1,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_storefront_events_occurred ON storefront_events(occurred_at, event);
+CREATE INDEX IF NOT EXISTS idx_storefront_events_product ON storefront_events(product_id, occurred_at)
+    WHERE product_id IS NOT NULL;
+
+ALTER TYPE report_kind ADD VALUE IF NOT EXISTS 'checkout_funnel';
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Migration: Stored Shipping Labels
+-- ============================================================================
+-- Labels bought for a shipment are kept in media storage (local disk or
+-- S3, `[media]`) instead of only as the carrier's URL, so they can be
+-- reprinted after the carrier's link expires and printed in batches for a
+-- pick list. label_key is the file's media storage key.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'label_format') THEN
+        CREATE TYPE label_format AS ENUM ('pdf', 'zpl', 'png');
+    END IF;
+END$$;
+
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS label_key TEXT;
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS label_format label_format;
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS label_stored_at TIMESTAMPTZ;
@@ -0,0 +1,17 @@
+-- ============================================================================
+-- Migration: Automatic Shipment Tracking
+-- ============================================================================
+-- Tracking updates pushed by EasyPost and ShipStation webhooks, or polled
+-- from DHL, FedEx, UPS and USPS by the tracking_poll job, move shipments
+-- to shipped and delivered. tracking_status is the carrier's last status
+-- (pre_transit, in_transit, delivered, exception...), tracking_checked_at
+-- when it was last polled or pushed.
+-- ============================================================================
+
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS tracking_status VARCHAR(32);
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS tracking_detail TEXT;
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS estimated_delivery TIMESTAMPTZ;
+ALTER TABLE fulfillments ADD COLUMN IF NOT EXISTS tracking_checked_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_fulfillments_tracking_poll ON fulfillments(tracking_checked_at NULLS FIRST)
+    WHERE tracking_number IS NOT NULL AND status IN ('pending', 'processing', 'shipped');
@@ -0,0 +1,25 @@
+-- ============================================================================
+-- Migration: Local Pickup
+-- ============================================================================
+-- Inventory locations with `pickup_enabled` offer free in-store pickup of
+-- what they have in stock. A pickup order records its location in
+-- `orders.pickup_location_id` and ships from there; its shipment becomes
+-- ready_for_pickup when packed and picked_up when collected.
+-- ============================================================================
+
+ALTER TYPE fulfillment_status ADD VALUE IF NOT EXISTS 'ready_for_pickup';
+ALTER TYPE fulfillment_status ADD VALUE IF NOT EXISTS 'picked_up';
+
+ALTER TABLE inventory_locations
+    ADD COLUMN IF NOT EXISTS pickup_enabled BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS pickup_instructions TEXT,
+    ADD COLUMN IF NOT EXISTS pickup_hours VARCHAR(255);
+
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS pickup_location_id UUID REFERENCES inventory_locations(id) ON DELETE SET NULL;
+
+ALTER TABLE fulfillments
+    ADD COLUMN IF NOT EXISTS ready_for_pickup_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS picked_up_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_orders_pickup_location ON orders(pickup_location_id) WHERE pickup_location_id IS NOT NULL;
@@ -0,0 +1,27 @@
+-- ============================================================================
+-- Migration: Login Sessions
+-- ============================================================================
+-- Each login starts a session holding the hash of its current refresh
+-- token. /auth/refresh rotates the token; presenting the previous one again
+-- means it was copied, so the session is revoked. Customers can list their
+-- sessions and revoke one device or all of them.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS login_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    refresh_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    previous_token_hash VARCHAR(64),
+    user_agent TEXT,
+    ip_address VARCHAR(45),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    revoked_reason TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_sessions_customer ON login_sessions(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_login_sessions_previous_token ON login_sessions(previous_token_hash)
+    WHERE previous_token_hash IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_login_sessions_expires ON login_sessions(expires_at);
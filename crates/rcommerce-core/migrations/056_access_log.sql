@@ -0,0 +1,23 @@
+-- ============================================================================
+-- Migration: Access Log
+-- ============================================================================
+-- One row per API request, for debugging partner integrations: who called
+-- what, with which result and how fast. Kept apart from the application logs
+-- and purged after access_log.retention_days. Client IPs are truncated to
+-- their network before writing; query strings and bodies are never stored.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS access_log (
+    id BIGSERIAL PRIMARY KEY,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    status SMALLINT NOT NULL,
+    latency_ms INTEGER NOT NULL,
+    api_key_prefix VARCHAR(64),
+    client_network VARCHAR(45),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_log_created ON access_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_access_log_api_key ON access_log(api_key_prefix, created_at DESC)
+    WHERE api_key_prefix IS NOT NULL;
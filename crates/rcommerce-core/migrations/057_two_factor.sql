@@ -0,0 +1,45 @@
+-- ============================================================================
+-- Migration: Two-Factor Authentication
+-- ============================================================================
+-- TOTP credentials (the shared secret sealed with the master key when one is
+-- configured), one-time backup codes stored as hashes, and the short-lived
+-- challenges a login answers with a code after the password.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS two_factor_credentials (
+    customer_id UUID PRIMARY KEY REFERENCES customers(id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    -- NULL until the first code is confirmed
+    confirmed_at TIMESTAMPTZ,
+    -- Last accepted 30-second step; a code is never accepted twice
+    last_used_step BIGINT,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS two_factor_backup_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (customer_id, code_hash)
+);
+
+CREATE TABLE IF NOT EXISTS two_factor_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    -- 'token' (bearer login) or 'cookie' (session login)
+    login_kind VARCHAR(16) NOT NULL,
+    user_agent TEXT,
+    ip_address VARCHAR(45),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_two_factor_challenges_expires ON two_factor_challenges(expires_at);
@@ -0,0 +1,45 @@
+-- ============================================================================
+-- Migration: OAuth / OpenID Connect Login
+-- ============================================================================
+-- Accounts at external identity providers (Google, Apple, GitHub) linked to
+-- customers, and the logins in flight: the state and PKCE verifier of a
+-- redirect to the provider, then the one-time code the storefront exchanges
+-- for tokens or a session after the callback.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS oauth_identities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    provider VARCHAR(32) NOT NULL,
+    -- The provider's stable account ID ("sub" claim, GitHub user ID)
+    subject VARCHAR(255) NOT NULL,
+    -- Email the provider reported at the last login
+    email VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_identities_customer ON oauth_identities(customer_id);
+
+CREATE TABLE IF NOT EXISTS oauth_logins (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider VARCHAR(32) NOT NULL,
+    state_hash VARCHAR(64) NOT NULL UNIQUE,
+    code_verifier VARCHAR(128) NOT NULL,
+    nonce VARCHAR(64) NOT NULL,
+    -- Storefront URL the browser returns to, and the state it passed
+    return_to TEXT NOT NULL,
+    client_state VARCHAR(255),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Set when the provider redirected back; a state is used once
+    callback_at TIMESTAMPTZ,
+    customer_id UUID REFERENCES customers(id) ON DELETE CASCADE,
+    created_customer BOOLEAN NOT NULL DEFAULT false,
+    login_code_hash VARCHAR(64) UNIQUE,
+    login_code_expires_at TIMESTAMPTZ,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_logins_expires ON oauth_logins(expires_at);
@@ -0,0 +1,56 @@
+-- ============================================================================
+-- Migration: Audit Log
+-- ============================================================================
+-- One row per mutating API call (POST/PUT/PATCH/DELETE): who made it (staff
+-- or customer JWT subject, or API key), what it touched, the resulting
+-- status, the client IP and, for resources with a snapshot table, the fields
+-- the call changed with their old and new values.
+--
+-- The table is append-only: rows can't be updated, and can only be deleted by
+-- the retention job, which sets rcommerce.audit_purge for its transaction.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    -- 'user' (JWT or session) or 'api_key'
+    actor_type VARCHAR(20) NOT NULL,
+    -- Customer/staff ID, or the API key's ID
+    actor_id UUID,
+    -- Email of the user, or name of the API key
+    actor_label VARCHAR(255),
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    -- Route template, e.g. /api/v1/products/:id
+    route TEXT,
+    resource_type VARCHAR(50),
+    resource_id VARCHAR(100),
+    status SMALLINT NOT NULL,
+    -- {"field": {"before": ..., "after": ...}}, credentials redacted
+    changes JSONB,
+    client_ip VARCHAR(45),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_resource ON audit_log(resource_type, resource_id, created_at DESC)
+    WHERE resource_id IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' AND current_setting('rcommerce.audit_purge', true) = 'on' THEN
+        RETURN OLD;
+    END IF;
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_log_append_only ON audit_log;
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
+
+DROP TRIGGER IF EXISTS audit_log_no_truncate ON audit_log;
+CREATE TRIGGER audit_log_no_truncate
+    BEFORE TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only();
@@ -0,0 +1,18 @@
+-- ============================================================================
+-- Migration: Soft Deletes
+-- ============================================================================
+-- Deleting a product, customer or order sets deleted_at instead of removing
+-- the row. Repositories leave deleted rows out of lookups and listings; they
+-- can be restored until the soft_delete_purge job removes them for good after
+-- soft_delete.retention_days. Slugs, emails and order numbers stay taken
+-- until then, so a restore never collides.
+-- ============================================================================
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- For the trash listings and the purge job
+CREATE INDEX IF NOT EXISTS idx_products_deleted ON products(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_customers_deleted ON customers(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_orders_deleted ON orders(deleted_at) WHERE deleted_at IS NOT NULL;
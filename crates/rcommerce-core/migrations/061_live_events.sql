@@ -0,0 +1,123 @@
+-- ============================================================================
+-- Migration: Live Events
+-- ============================================================================
+-- Publishes order, payment, low stock and shipment changes on the
+-- `live_events` channel (LISTEN/NOTIFY) for the admin WebSocket.
+--
+-- Events are sent by triggers, so every code path that changes these rows
+-- (API, jobs, webhooks, CLI, direct SQL) is seen. NOTIFY is delivered on
+-- commit, so rolled back changes are never announced.
+-- ============================================================================
+
+-- Send one event: {"topic": ..., "data": ..., "at": ...}
+CREATE OR REPLACE FUNCTION notify_live_event(topic TEXT, data JSONB)
+RETURNS VOID AS $$
+BEGIN
+    PERFORM pg_notify(
+        'live_events',
+        jsonb_build_object('topic', topic, 'data', data, 'at', NOW())::TEXT
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- order.created
+CREATE OR REPLACE FUNCTION live_order_created()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM notify_live_event('order.created', jsonb_build_object(
+        'order_id', NEW.id,
+        'order_number', NEW.order_number,
+        'customer_id', NEW.customer_id,
+        'email', NEW.email,
+        'total', NEW.total,
+        'currency', NEW.currency,
+        'status', NEW.status
+    ));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS orders_live_created ON orders;
+CREATE TRIGGER orders_live_created
+    AFTER INSERT ON orders
+    FOR EACH ROW
+    WHEN (NOT NEW.draft)
+    EXECUTE FUNCTION live_order_created();
+
+-- payment.updated: new payments and status changes
+CREATE OR REPLACE FUNCTION live_payment_updated()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+        PERFORM notify_live_event('payment.updated', jsonb_build_object(
+            'payment_id', NEW.id,
+            'order_id', NEW.order_id,
+            'status', NEW.status,
+            'previous_status', CASE WHEN TG_OP = 'UPDATE' THEN OLD.status END,
+            'amount', NEW.amount,
+            'currency', NEW.currency,
+            'gateway', NEW.gateway
+        ));
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS payments_live_updated ON payments;
+CREATE TRIGGER payments_live_updated
+    AFTER INSERT OR UPDATE ON payments
+    FOR EACH ROW
+    EXECUTE FUNCTION live_payment_updated();
+
+-- inventory.low_stock: available quantity falls to the reorder point
+CREATE OR REPLACE FUNCTION live_inventory_low_stock()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.reorder_point > 0
+        AND NEW.available_quantity <= NEW.reorder_point
+        AND OLD.available_quantity > OLD.reorder_point
+    THEN
+        PERFORM notify_live_event('inventory.low_stock', jsonb_build_object(
+            'product_id', NEW.product_id,
+            'variant_id', NEW.variant_id,
+            'location_id', NEW.location_id,
+            'available_quantity', NEW.available_quantity,
+            'reorder_point', NEW.reorder_point
+        ));
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS inventory_levels_live_low_stock ON inventory_levels;
+CREATE TRIGGER inventory_levels_live_low_stock
+    AFTER UPDATE ON inventory_levels
+    FOR EACH ROW
+    EXECUTE FUNCTION live_inventory_low_stock();
+
+-- shipment.status_changed: fulfillment or carrier tracking status changes
+CREATE OR REPLACE FUNCTION live_shipment_status_changed()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.status IS DISTINCT FROM OLD.status
+        OR NEW.tracking_status IS DISTINCT FROM OLD.tracking_status
+    THEN
+        PERFORM notify_live_event('shipment.status_changed', jsonb_build_object(
+            'shipment_id', NEW.id,
+            'order_id', NEW.order_id,
+            'status', NEW.status,
+            'previous_status', OLD.status,
+            'tracking_status', NEW.tracking_status,
+            'tracking_number', NEW.tracking_number,
+            'tracking_company', NEW.tracking_company
+        ));
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS fulfillments_live_status ON fulfillments;
+CREATE TRIGGER fulfillments_live_status
+    AFTER UPDATE ON fulfillments
+    FOR EACH ROW
+    EXECUTE FUNCTION live_shipment_status_changed();
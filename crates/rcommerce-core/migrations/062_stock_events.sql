@@ -0,0 +1,90 @@
+-- ============================================================================
+-- Migration: Stock Events
+-- ============================================================================
+-- Announces products and variants going out of stock or coming back on the
+-- `live_events` channel (see 061_live_events), for storefront stock badges
+-- (GET /api/v1/storefront/stock/events) and admin dashboards.
+--
+-- Only availability is published, never quantities.
+-- ============================================================================
+
+-- Whether a product or variant can be bought with this stock
+CREATE OR REPLACE FUNCTION stock_available(
+    managed BOOLEAN,
+    policy inventory_policy,
+    quantity INTEGER,
+    continues_selling BOOLEAN
+)
+RETURNS BOOLEAN AS $$
+    SELECT NOT managed OR continues_selling OR policy = 'continue' OR quantity > 0;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Send stock.availability_changed when availability flips
+CREATE OR REPLACE FUNCTION notify_stock_change(
+    product UUID,
+    variant UUID,
+    was_available BOOLEAN,
+    is_available BOOLEAN
+)
+RETURNS VOID AS $$
+BEGIN
+    IF was_available IS DISTINCT FROM is_available THEN
+        PERFORM notify_live_event('stock.availability_changed', jsonb_build_object(
+            'product_id', product,
+            'variant_id', variant,
+            'status', CASE WHEN is_available THEN 'back_in_stock' ELSE 'out_of_stock' END
+        ));
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION live_product_stock()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM notify_stock_change(
+        NEW.id,
+        NULL,
+        stock_available(OLD.inventory_management, OLD.inventory_policy, OLD.inventory_quantity, OLD.continues_selling_when_out_of_stock),
+        stock_available(NEW.inventory_management, NEW.inventory_policy, NEW.inventory_quantity, NEW.continues_selling_when_out_of_stock)
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_live_stock ON products;
+CREATE TRIGGER products_live_stock
+    AFTER UPDATE OF inventory_quantity, inventory_policy, inventory_management, continues_selling_when_out_of_stock
+    ON products
+    FOR EACH ROW
+    WHEN (NEW.is_active AND NEW.deleted_at IS NULL)
+    EXECUTE FUNCTION live_product_stock();
+
+-- Variants of products without inventory management are always available
+CREATE OR REPLACE FUNCTION live_variant_stock()
+RETURNS TRIGGER AS $$
+DECLARE
+    managed BOOLEAN;
+BEGIN
+    SELECT inventory_management INTO managed
+    FROM products
+    WHERE id = NEW.product_id AND is_active AND deleted_at IS NULL;
+
+    IF managed THEN
+        PERFORM notify_stock_change(
+            NEW.product_id,
+            NEW.id,
+            stock_available(true, OLD.inventory_policy, OLD.inventory_quantity, false),
+            stock_available(true, NEW.inventory_policy, NEW.inventory_quantity, false)
+        );
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS product_variants_live_stock ON product_variants;
+CREATE TRIGGER product_variants_live_stock
+    AFTER UPDATE OF inventory_quantity, inventory_policy
+    ON product_variants
+    FOR EACH ROW
+    WHEN (NEW.is_active)
+    EXECUTE FUNCTION live_variant_stock();
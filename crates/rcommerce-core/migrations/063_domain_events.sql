@@ -0,0 +1,21 @@
+-- ============================================================================
+-- Migration: Domain Events
+-- ============================================================================
+-- Orders, payments, low stock and shipment changes are now published by the
+-- code that makes them, on the event bus, and reach live sockets, webhooks
+-- and notifications from there. Their 061_live_events triggers would send
+-- them twice.
+--
+-- notify_live_event and the 062 stock availability triggers stay: stock
+-- changes from any write still reach the bus through `live_events`.
+-- ============================================================================
+
+DROP TRIGGER IF EXISTS orders_live_created ON orders;
+DROP TRIGGER IF EXISTS payments_live_updated ON payments;
+DROP TRIGGER IF EXISTS inventory_levels_live_low_stock ON inventory_levels;
+DROP TRIGGER IF EXISTS fulfillments_live_status ON fulfillments;
+
+DROP FUNCTION IF EXISTS live_order_created();
+DROP FUNCTION IF EXISTS live_payment_updated();
+DROP FUNCTION IF EXISTS live_inventory_low_stock();
+DROP FUNCTION IF EXISTS live_shipment_status_changed();
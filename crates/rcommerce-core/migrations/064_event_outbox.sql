@@ -0,0 +1,31 @@
+-- ============================================================================
+-- Migration: Event Outbox
+-- ============================================================================
+-- Domain events written in the same transaction as the change they describe,
+-- so an order committed just before a crash still reaches webhooks, customer
+-- notices and live dashboards. The outbox relay of each instance claims
+-- pending rows, publishes them to the event bus and marks them processed.
+--
+-- The row id is the event id, so a row published twice (the relay died
+-- before marking it) keeps its X-Webhook-Event-Id.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- A relay is publishing the row until then
+    claimed_until TIMESTAMPTZ,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_pending
+    ON event_outbox (created_at)
+    WHERE processed_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_processed
+    ON event_outbox (processed_at)
+    WHERE processed_at IS NOT NULL;
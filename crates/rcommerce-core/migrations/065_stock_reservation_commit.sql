@@ -0,0 +1,80 @@
+-- ============================================================================
+-- Migration: Stock Reservation Commit
+-- ============================================================================
+-- New orders reserve their stock in the order's transaction, with the stock
+-- levels locked, so concurrent checkouts can't sell the same units. Until the
+-- order is paid a reservation only holds the units (available stock minus
+-- active, unexpired reservations is what can be sold); it stops holding them
+-- once reservation_timeout_minutes pass.
+--
+-- When the order is paid its reservations, expired or not, become stock
+-- decrements: the units leave available_quantity for reserved_quantity,
+-- where they wait to be shipped. Shipments take the order's committed units
+-- from reserved_quantity before any available stock. Cancelling the order
+-- puts committed units that haven't shipped back into available_quantity.
+--
+-- Both happen here so every path that marks orders paid or cancelled (the
+-- order service, payment webhooks, admin edits) moves the stock.
+-- ============================================================================
+
+CREATE INDEX IF NOT EXISTS idx_stock_reservations_holds
+    ON stock_reservations (product_id, location_id)
+    WHERE status = 'active';
+
+CREATE INDEX IF NOT EXISTS idx_stock_reservations_committed
+    ON stock_reservations (order_id, product_id, location_id)
+    WHERE status = 'committed';
+
+CREATE OR REPLACE FUNCTION move_order_reserved_stock() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.payment_status = 'paid' AND OLD.payment_status IS DISTINCT FROM 'paid' THEN
+        WITH committed AS (
+            UPDATE stock_reservations
+            SET status = 'committed'
+            WHERE order_id = NEW.id AND status IN ('active', 'expired')
+            RETURNING product_id, variant_id, location_id, quantity
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM committed
+            GROUP BY product_id, variant_id, location_id
+        )
+        UPDATE inventory_levels il
+        SET available_quantity = il.available_quantity - t.quantity,
+            reserved_quantity = il.reserved_quantity + t.quantity,
+            updated_at = NOW()
+        FROM totals t
+        WHERE il.product_id = t.product_id
+          AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+          AND il.location_id = t.location_id;
+    END IF;
+
+    IF NEW.status = 'cancelled' AND OLD.status IS DISTINCT FROM 'cancelled' THEN
+        WITH released AS (
+            UPDATE stock_reservations r
+            SET status = 'released'
+            FROM stock_reservations prior
+            WHERE prior.id = r.id AND r.order_id = NEW.id AND r.status IN ('active', 'expired', 'committed')
+            RETURNING r.product_id, r.variant_id, r.location_id, r.quantity, prior.status AS previous_status
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM released
+            WHERE previous_status = 'committed'
+            GROUP BY product_id, variant_id, location_id
+        )
+        UPDATE inventory_levels il
+        SET available_quantity = il.available_quantity + t.quantity,
+            reserved_quantity = GREATEST(il.reserved_quantity - t.quantity, 0),
+            updated_at = NOW()
+        FROM totals t
+        WHERE il.product_id = t.product_id
+          AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+          AND il.location_id = t.location_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_orders_reserved_stock ON orders;
+CREATE TRIGGER trg_orders_reserved_stock
+    AFTER UPDATE OF status, payment_status ON orders
+    FOR EACH ROW EXECUTE FUNCTION move_order_reserved_stock();
@@ -0,0 +1,25 @@
+-- ============================================================================
+-- Migration: Order Number Sequences
+-- ============================================================================
+-- Orders keep their UUID and get a human-friendly order_number from a
+-- sequence configured under [order_numbers] (prefix, padding, yearly reset).
+-- A sequence is the default one or a channel's own; with yearly reset each
+-- year (period) counts separately, otherwise period is 0.
+--
+-- Numbers are taken with an upsert on the order's transaction, which keeps
+-- the row locked until the order commits or rolls back, so sequences have
+-- no gaps. Existing orders keep their numbers.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS order_number_sequences (
+    scope VARCHAR(20) NOT NULL,
+    period INTEGER NOT NULL DEFAULT 0,
+    last_value BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, period)
+);
+
+-- Case-insensitive lookups by order number
+CREATE INDEX IF NOT EXISTS idx_orders_order_number_lower
+    ON orders (LOWER(order_number));
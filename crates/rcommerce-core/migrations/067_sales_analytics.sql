@@ -0,0 +1,78 @@
+-- ============================================================================
+-- Migration: Sales Analytics
+-- ============================================================================
+-- Daily sales rollups behind /api/v1/admin/sales and `rcommerce report`.
+-- Reading them never scans orders: the `sales_views_refresh` job refreshes
+-- the views concurrently (every `[reports] sales_refresh_interval_secs`), so
+-- figures lag orders by up to one interval. Days are UTC.
+--
+-- Amounts are in the base currency (order currency times exchange_rate).
+-- Orders count as paid once payment_status is paid or refunded; refunds are
+-- counted on the day of their order. Drafts and deleted orders are left out,
+-- and archived orders drop out at the next refresh.
+-- ============================================================================
+
+-- Orders, paid orders, items, revenue and refunds per day and channel
+CREATE MATERIALIZED VIEW IF NOT EXISTS sales_daily AS
+SELECT (o.created_at AT TIME ZONE 'UTC')::DATE AS day,
+       o.channel,
+       COUNT(*)::BIGINT AS orders,
+       COUNT(*) FILTER (WHERE o.payment_status IN ('paid', 'refunded'))::BIGINT AS paid_orders,
+       COALESCE(SUM(items.quantity) FILTER (WHERE o.payment_status IN ('paid', 'refunded')), 0)::BIGINT AS items_sold,
+       COALESCE(ROUND(SUM(o.total * COALESCE(o.exchange_rate, 1))
+           FILTER (WHERE o.payment_status IN ('paid', 'refunded')), 2), 0)::DECIMAL(20, 2) AS gross_revenue,
+       COUNT(*) FILTER (WHERE refunds.amount > 0)::BIGINT AS refunded_orders,
+       COALESCE(ROUND(SUM(refunds.amount * COALESCE(o.exchange_rate, 1)), 2), 0)::DECIMAL(20, 2) AS refunded_amount
+FROM orders o
+LEFT JOIN LATERAL (
+    SELECT SUM(quantity) AS quantity FROM order_items WHERE order_id = o.id
+) items ON true
+LEFT JOIN LATERAL (
+    SELECT SUM(amount) AS amount FROM refunds WHERE order_id = o.id AND status = 'refunded'
+) refunds ON true
+WHERE NOT o.draft AND o.deleted_at IS NULL
+GROUP BY 1, 2;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sales_daily_day_channel
+    ON sales_daily (day, channel);
+
+-- Units and revenue of paid orders per day and product
+CREATE MATERIALIZED VIEW IF NOT EXISTS sales_product_daily AS
+SELECT (o.created_at AT TIME ZONE 'UTC')::DATE AS day,
+       i.product_id,
+       SUM(i.quantity)::BIGINT AS units,
+       COUNT(DISTINCT o.id)::BIGINT AS orders,
+       ROUND(SUM(i.total * COALESCE(o.exchange_rate, 1)), 2)::DECIMAL(20, 2) AS revenue
+FROM order_items i
+JOIN orders o ON o.id = i.order_id
+WHERE i.product_id IS NOT NULL
+  AND o.payment_status IN ('paid', 'refunded')
+  AND NOT o.draft AND o.deleted_at IS NULL
+GROUP BY 1, 2;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sales_product_daily_day_product
+    ON sales_product_daily (day, product_id);
+
+-- Storefront sessions per day, scaled up by the sample rate (see
+-- storefront_events); a session spanning midnight counts on both days
+CREATE MATERIALIZED VIEW IF NOT EXISTS storefront_sessions_daily AS
+SELECT day, SUM(weight)::FLOAT8 AS sessions
+FROM (
+    SELECT (occurred_at AT TIME ZONE 'UTC')::DATE AS day, session_hash, MAX(weight) AS weight
+    FROM storefront_events
+    GROUP BY 1, 2
+) sessions
+GROUP BY day;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storefront_sessions_daily_day
+    ON storefront_sessions_daily (day);
+
+-- When the views were last refreshed, shown with every report
+CREATE TABLE IF NOT EXISTS sales_view_refreshes (
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    refreshed_at TIMESTAMPTZ NOT NULL
+);
+
+INSERT INTO sales_view_refreshes (id, refreshed_at)
+VALUES (true, NOW())
+ON CONFLICT (id) DO NOTHING;
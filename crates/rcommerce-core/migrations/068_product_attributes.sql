@@ -0,0 +1,86 @@
+-- ============================================================================
+-- Migration: Product Attributes
+-- ============================================================================
+-- Typed, shop-wide attributes (size, color, material, ...) with allowed
+-- values, assigned to products and variants. Select attributes take values
+-- from their options; number, boolean and text attributes hold their own.
+-- Filterable attributes narrow product listings (`?attr=color:red`) and are
+-- counted as facets.
+--
+-- A variant's values also count for its product, so a shirt with a red
+-- variant is listed under color:red. The legacy per-product attribute
+-- structs were never backed by tables and are replaced by these.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'attribute_type') THEN
+        CREATE TYPE attribute_type AS ENUM ('text', 'number', 'boolean', 'select', 'multi_select');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS attributes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Used in filters and imports, e.g. `color`; never changes
+    code VARCHAR(64) NOT NULL UNIQUE CHECK (code ~ '^[a-z0-9_]+$'),
+    name VARCHAR(100) NOT NULL,
+    attribute_type attribute_type NOT NULL,
+    -- Shown after number values, e.g. `cm`
+    unit VARCHAR(20),
+    filterable BOOLEAN NOT NULL DEFAULT true,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS update_attributes_updated_at ON attributes;
+CREATE TRIGGER update_attributes_updated_at
+    BEFORE UPDATE ON attributes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Allowed values of select and multi_select attributes
+CREATE TABLE IF NOT EXISTS attribute_options (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    attribute_id UUID NOT NULL REFERENCES attributes(id) ON DELETE CASCADE,
+    value VARCHAR(100) NOT NULL,
+    -- Hex color of a color swatch, e.g. `#ff0000`
+    swatch VARCHAR(7) CHECK (swatch ~ '^#[0-9a-fA-F]{6}$'),
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_attribute_options_value
+    ON attribute_options (attribute_id, LOWER(value));
+
+-- One row per value; multi_select attributes have a row per option
+CREATE TABLE IF NOT EXISTS product_attribute_values (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- NULL for the product's own values
+    variant_id UUID REFERENCES product_variants(id) ON DELETE CASCADE,
+    attribute_id UUID NOT NULL REFERENCES attributes(id) ON DELETE CASCADE,
+    option_id UUID REFERENCES attribute_options(id) ON DELETE CASCADE,
+    text_value TEXT,
+    number_value DECIMAL(20, 4),
+    boolean_value BOOLEAN,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls(option_id, text_value, number_value, boolean_value) = 1)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_attribute_values_unique
+    ON product_attribute_values (
+        product_id,
+        COALESCE(variant_id, '00000000-0000-0000-0000-000000000000'::UUID),
+        attribute_id,
+        COALESCE(option_id, '00000000-0000-0000-0000-000000000000'::UUID)
+    );
+
+-- Listing filters and facet counts
+CREATE INDEX IF NOT EXISTS idx_product_attribute_values_option
+    ON product_attribute_values (attribute_id, option_id, product_id);
+CREATE INDEX IF NOT EXISTS idx_product_attribute_values_number
+    ON product_attribute_values (attribute_id, number_value)
+    WHERE number_value IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_product_attribute_values_variant
+    ON product_attribute_values (variant_id)
+    WHERE variant_id IS NOT NULL;
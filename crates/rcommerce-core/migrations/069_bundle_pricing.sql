@@ -0,0 +1,82 @@
+-- ============================================================================
+-- Migration: Bundle Pricing
+-- ============================================================================
+-- Keeps the price of bundles priced from their components (`sum` and
+-- `percentage_discount`) in step with the components: it is recomputed when
+-- a component is added, changed or removed, when a component's price
+-- changes and when the bundle's strategy or discount changes. Bundles of
+-- bundles follow, as a bundle's new price reprices the bundles containing it.
+-- Optional components are not counted. `fixed` bundles keep their price.
+-- ============================================================================
+
+-- Sum of the required components, less the discount, to the cent
+CREATE OR REPLACE FUNCTION refresh_bundle_price(bundle_id UUID)
+RETURNS VOID AS $$
+BEGIN
+    UPDATE products b
+    SET price = c.price
+    FROM (
+        SELECT ROUND(
+            COALESCE(SUM(p.price * bc.quantity), 0)
+                * (1 - CASE WHEN bp.bundle_pricing_strategy = 'percentage_discount'
+                            THEN COALESCE(bp.bundle_discount_percentage, 0) / 100
+                            ELSE 0 END),
+            2
+        ) AS price
+        FROM products bp
+        LEFT JOIN bundle_components bc ON bc.bundle_product_id = bp.id AND NOT bc.is_optional
+        LEFT JOIN products p ON p.id = bc.component_product_id
+        WHERE bp.id = bundle_id
+        GROUP BY bp.id
+    ) c
+    WHERE b.id = bundle_id
+      AND b.product_type = 'bundle'
+      AND b.bundle_pricing_strategy IN ('sum', 'percentage_discount')
+      AND b.price IS DISTINCT FROM c.price;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION bundle_components_reprice()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM refresh_bundle_price(OLD.bundle_product_id);
+        RETURN OLD;
+    END IF;
+    PERFORM refresh_bundle_price(NEW.bundle_product_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS bundle_components_reprice ON bundle_components;
+CREATE TRIGGER bundle_components_reprice
+    AFTER INSERT OR UPDATE OR DELETE ON bundle_components
+    FOR EACH ROW
+    EXECUTE FUNCTION bundle_components_reprice();
+
+CREATE OR REPLACE FUNCTION products_reprice_bundles()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.product_type = 'bundle' AND (
+        NEW.bundle_pricing_strategy IS DISTINCT FROM OLD.bundle_pricing_strategy
+        OR NEW.bundle_discount_percentage IS DISTINCT FROM OLD.bundle_discount_percentage
+    ) THEN
+        PERFORM refresh_bundle_price(NEW.id);
+    END IF;
+    IF NEW.price IS DISTINCT FROM OLD.price THEN
+        PERFORM refresh_bundle_price(bc.bundle_product_id)
+        FROM bundle_components bc
+        WHERE bc.component_product_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS products_reprice_bundles ON products;
+CREATE TRIGGER products_reprice_bundles
+    AFTER UPDATE OF price, bundle_pricing_strategy, bundle_discount_percentage ON products
+    FOR EACH ROW
+    EXECUTE FUNCTION products_reprice_bundles();
+
+-- Bring existing bundles in line
+SELECT refresh_bundle_price(id) FROM products WHERE product_type = 'bundle';
@@ -0,0 +1,47 @@
+-- ============================================================================
+-- Migration: Fraud Screening
+-- ============================================================================
+-- Checkout screens orders before they are paid. The risk score (0-100) is
+-- kept on the order; the assessment records the signals behind it and the
+-- action taken. Orders held for review wait in the review queue
+-- (review_status 'pending') until staff approve or reject them.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'risk_action') THEN
+        CREATE TYPE risk_action AS ENUM ('allow', 'require_3ds', 'review', 'cancel');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'risk_review_status') THEN
+        CREATE TYPE risk_review_status AS ENUM ('pending', 'approved', 'rejected');
+    END IF;
+END$$;
+
+-- NULL for orders placed without screening
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS risk_score INTEGER
+    CHECK (risk_score BETWEEN 0 AND 100);
+
+CREATE TABLE IF NOT EXISTS order_risk_assessments (
+    order_id UUID PRIMARY KEY REFERENCES orders(id) ON DELETE CASCADE,
+    score INTEGER NOT NULL CHECK (score BETWEEN 0 AND 100),
+    action risk_action NOT NULL,
+    -- [{"code": "velocity", "score": 40, "reason": "..."}]
+    signals JSONB NOT NULL DEFAULT '[]',
+    -- Kept for velocity checks
+    email VARCHAR(255) NOT NULL,
+    client_ip VARCHAR(45),
+    review_status risk_review_status,
+    reviewed_by UUID,
+    reviewed_at TIMESTAMPTZ,
+    review_note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_risk_assessments_queue
+    ON order_risk_assessments (created_at)
+    WHERE review_status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_order_risk_assessments_ip
+    ON order_risk_assessments (client_ip, created_at)
+    WHERE client_ip IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_order_risk_assessments_email
+    ON order_risk_assessments (LOWER(email), created_at);
@@ -0,0 +1,44 @@
+-- ============================================================================
+-- Migration: Payment Sessions
+-- ============================================================================
+-- One row per payment started through the payments API. It remembers the
+-- gateway and the storefront page to return to, so 3-D Secure challenges can
+-- be completed from the gateway's return redirect and polled for status.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'payment_session_status') THEN
+        CREATE TYPE payment_session_status AS ENUM (
+            'pending', 'requires_action', 'processing', 'succeeded', 'failed', 'cancelled', 'expired'
+        );
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS payment_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    gateway VARCHAR(50) NOT NULL,
+    -- Set once the gateway has answered
+    gateway_payment_id VARCHAR(255),
+    amount DECIMAL(20, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    status payment_session_status NOT NULL DEFAULT 'pending',
+    -- The pending action: a ThreeDsChallenge for three_d_secure
+    action_type VARCHAR(50),
+    action_data JSONB,
+    -- Storefront page the customer is sent to after a challenge
+    return_url TEXT,
+    error_code VARCHAR(100),
+    error_message TEXT,
+    receipt_url TEXT,
+    expires_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_sessions_gateway_payment
+    ON payment_sessions (gateway, gateway_payment_id)
+    WHERE gateway_payment_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_payment_sessions_order ON payment_sessions (order_id, created_at);
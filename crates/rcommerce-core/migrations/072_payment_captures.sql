@@ -0,0 +1,49 @@
+-- ============================================================================
+-- Migration: Payment Captures
+-- ============================================================================
+-- Payments can be authorized first and captured later, in one or several
+-- captures (e.g. one per shipment), or voided. Authorizations lapse at the
+-- gateway after a few days; the authorization expiry job voids them first.
+-- ============================================================================
+
+ALTER TYPE payment_session_status ADD VALUE IF NOT EXISTS 'authorized';
+
+-- authorized_amount is NULL for payments captured at once
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS authorized_amount DECIMAL(20, 2);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS captured_amount DECIMAL(20, 2) NOT NULL DEFAULT 0;
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS authorization_expires_at TIMESTAMPTZ;
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS voided_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_payments_authorization_expiry
+    ON payments(authorization_expires_at) WHERE status = 'authorized';
+
+-- Capture each shipment's share of its order's authorization as it ships;
+-- NULL follows payment.capture.on_fulfillment
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS capture_on_fulfillment BOOLEAN;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'capture_source') THEN
+        CREATE TYPE capture_source AS ENUM ('manual', 'fulfillment');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS payment_captures (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    amount DECIMAL(20, 2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    gateway_capture_id VARCHAR(255),
+    -- The rest of the authorization was released with this capture
+    final_capture BOOLEAN NOT NULL DEFAULT false,
+    source capture_source NOT NULL DEFAULT 'manual',
+    shipment_id UUID REFERENCES fulfillments(id) ON DELETE SET NULL,
+    captured_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_captures_payment ON payment_captures(payment_id, created_at);
+-- A shipment is captured once
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_captures_shipment
+    ON payment_captures(shipment_id) WHERE shipment_id IS NOT NULL;
@@ -0,0 +1,62 @@
+-- ============================================================================
+-- Migration: Payout Reconciliation
+-- ============================================================================
+-- Payouts imported from gateways, with the balance transactions each paid
+-- out, matched against recorded payments and refunds. Lines that don't
+-- match (unknown references, differing amounts) are flagged for finance.
+-- ============================================================================
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'settlement_kind') THEN
+        CREATE TYPE settlement_kind AS ENUM ('charge', 'refund', 'fee', 'adjustment');
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'reconciliation_status') THEN
+        CREATE TYPE reconciliation_status AS ENUM ('matched', 'amount_mismatch', 'unmatched', 'fee', 'adjustment');
+    END IF;
+END$$;
+
+CREATE TABLE IF NOT EXISTS payouts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    gateway VARCHAR(50) NOT NULL,
+    gateway_payout_id VARCHAR(255) NOT NULL,
+    -- Net amount paid out
+    amount DECIMAL(20, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    gross DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    fees DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    arrival_date TIMESTAMPTZ,
+    gateway_created_at TIMESTAMPTZ NOT NULL,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (gateway, gateway_payout_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payouts_arrival ON payouts(arrival_date);
+
+CREATE TABLE IF NOT EXISTS payout_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payout_id UUID NOT NULL REFERENCES payouts(id) ON DELETE CASCADE,
+    gateway_transaction_id VARCHAR(255) NOT NULL,
+    kind settlement_kind NOT NULL,
+    -- Gateway payment or refund ID
+    reference VARCHAR(255),
+    -- Gross amount; negative for refunds
+    amount DECIMAL(20, 2) NOT NULL,
+    fee DECIMAL(20, 2) NOT NULL DEFAULT 0,
+    net DECIMAL(20, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    description TEXT,
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    refund_id UUID REFERENCES refunds(id) ON DELETE SET NULL,
+    -- What was recorded, in the recorded currency
+    expected_amount DECIMAL(20, 2),
+    expected_currency VARCHAR(3),
+    status reconciliation_status NOT NULL,
+    UNIQUE (payout_id, gateway_transaction_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payout_lines_payment ON payout_lines(payment_id) WHERE payment_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_payout_lines_refund ON payout_lines(refund_id) WHERE refund_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_payout_lines_flagged
+    ON payout_lines(payout_id) WHERE status IN ('amount_mismatch', 'unmatched');
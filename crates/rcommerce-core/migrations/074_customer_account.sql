@@ -0,0 +1,30 @@
+-- ============================================================================
+-- Migration: Customer Account
+-- ============================================================================
+-- Payment methods customers saved from their account. The card stays with
+-- the gateway; this records whose token it is, so customers only see and
+-- remove their own, and what to show for it. Closing an account removes
+-- them at the gateway and here.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS customer_payment_methods (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    gateway VARCHAR(50) NOT NULL,
+    token VARCHAR(255) NOT NULL,
+    method_type VARCHAR(32) NOT NULL,
+    card_brand VARCHAR(32),
+    last_four VARCHAR(4),
+    exp_month VARCHAR(2),
+    exp_year VARCHAR(4),
+    cardholder_name VARCHAR(255),
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (gateway, token)
+);
+
+CREATE INDEX IF NOT EXISTS idx_customer_payment_methods_customer ON customer_payment_methods(customer_id);
+
+-- At most one default per customer
+CREATE UNIQUE INDEX IF NOT EXISTS idx_customer_payment_methods_default
+    ON customer_payment_methods(customer_id) WHERE is_default;
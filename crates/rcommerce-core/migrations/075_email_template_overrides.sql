@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migration: Email Template Overrides
+-- ============================================================================
+-- Active stored email templates now replace the built-in ones. The
+-- placeholder templates seeded with the notifications tables were never
+-- sent and are much plainer than the built-ins (`shipping_notification` is
+-- not even a template type), so the ones nobody edited are deactivated.
+-- ============================================================================
+
+UPDATE notification_templates
+SET is_active = false
+WHERE name IN ('order_confirmation', 'shipping_notification', 'password_reset')
+  AND html_template IS NULL
+  AND updated_at = created_at;
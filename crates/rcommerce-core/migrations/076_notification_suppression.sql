@@ -0,0 +1,11 @@
+-- ============================================================================
+-- Migration: Notification Suppression
+-- ============================================================================
+-- Notifications a customer opted out of (a turned off channel or category,
+-- or an unsubscribe link) are kept with a 'suppressed' status instead of
+-- being sent. Recipients are matched to customers by email or phone.
+-- ============================================================================
+
+ALTER TYPE delivery_status ADD VALUE IF NOT EXISTS 'suppressed';
+
+CREATE INDEX IF NOT EXISTS idx_customers_phone ON customers (phone) WHERE phone IS NOT NULL;
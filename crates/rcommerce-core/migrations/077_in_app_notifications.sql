@@ -0,0 +1,53 @@
+-- ============================================================================
+-- Migration: In-App Notifications
+-- ============================================================================
+-- Notifications shown in the admin UI and customer portal (the notification
+-- bell), for customers and staff alike. New notifications and reads are
+-- announced on the `in_app_notifications` channel so every instance can push
+-- them to the recipient's open sockets; the payload only carries ids.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS in_app_notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    -- The queued notification it was sent as, if any
+    notification_id UUID,
+    category VARCHAR(50) NOT NULL DEFAULT 'account',
+    title VARCHAR(255) NOT NULL,
+    body TEXT NOT NULL DEFAULT '',
+    link TEXT,
+    data JSONB NOT NULL DEFAULT '{}',
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_in_app_notifications_customer
+    ON in_app_notifications(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_in_app_notifications_unread
+    ON in_app_notifications(customer_id) WHERE read_at IS NULL;
+
+-- Reads carry no notification id, so marking many read in one transaction
+-- sends a single (deduplicated) notification
+CREATE OR REPLACE FUNCTION notify_in_app_notification()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('in_app_notifications', jsonb_build_object(
+        'customer_id', NEW.customer_id,
+        'notification_id', CASE WHEN TG_OP = 'INSERT' THEN NEW.id END
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS in_app_notifications_created ON in_app_notifications;
+CREATE TRIGGER in_app_notifications_created
+    AFTER INSERT ON in_app_notifications
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_in_app_notification();
+
+DROP TRIGGER IF EXISTS in_app_notifications_read ON in_app_notifications;
+CREATE TRIGGER in_app_notifications_read
+    AFTER UPDATE OF read_at ON in_app_notifications
+    FOR EACH ROW
+    WHEN (OLD.read_at IS DISTINCT FROM NEW.read_at)
+    EXECUTE FUNCTION notify_in_app_notification();
@@ -0,0 +1,29 @@
+-- ============================================================================
+-- Migration: Job Dead Letters
+-- ============================================================================
+-- A failed recurring job is retried with exponential backoff (per-job
+-- policies under [scheduler]); `failures` keeps each failed attempt until a
+-- run succeeds. A job out of attempts moves its failures to
+-- job_dead_letters, where staff retry (one-off run) or discard it through
+-- /api/v1/admin/jobs/failed or `rcommerce jobs retry|discard`.
+-- ============================================================================
+
+ALTER TABLE scheduled_jobs
+    ADD COLUMN IF NOT EXISTS failed_attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS failures JSONB NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS job_dead_letters (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_name VARCHAR(100) NOT NULL REFERENCES scheduled_jobs(name) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL,
+    -- Error of the last attempt
+    error TEXT NOT NULL,
+    -- Every attempt: attempt, error, instance, duration_ms, failed_at
+    failures JSONB NOT NULL DEFAULT '[]',
+    status VARCHAR(20) NOT NULL DEFAULT 'dead' CHECK (status IN ('dead', 'retried', 'discarded')),
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_dead_letters_status
+    ON job_dead_letters (status, created_at DESC);
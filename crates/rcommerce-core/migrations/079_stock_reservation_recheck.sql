@@ -0,0 +1,138 @@
+-- ============================================================================
+-- Migration: Stock Reservation Re-check
+-- ============================================================================
+-- A reservation stops holding its units once reservation_timeout_minutes
+-- pass, so by the time a late payment arrives they may have been reserved
+-- and sold to another order. Paying an order now only turns its live
+-- reservations (active and unexpired) into stock decrements; 065 committed
+-- expired ones too, even when the stock was gone.
+--
+-- Each lapsed reservation (expired, or active past its expiry but not yet
+-- marked) is checked again with the product's stock levels locked, as
+-- InventoryService::reserve_for_order does: it is committed at the active
+-- location with the most free stock if that covers it (or the product allows
+-- backorders). Otherwise it stays expired, the order goes on hold, and a
+-- `stock_shortfall` order event lists what is missing for staff to restock
+-- or refund.
+-- ============================================================================
+
+CREATE OR REPLACE FUNCTION move_order_reserved_stock() RETURNS TRIGGER AS $$
+DECLARE
+    lapsed RECORD;
+    best_location UUID;
+    best_free BIGINT;
+    shortfalls JSONB := '[]'::JSONB;
+BEGIN
+    IF NEW.payment_status = 'paid' AND OLD.payment_status IS DISTINCT FROM 'paid' THEN
+        WITH committed AS (
+            UPDATE stock_reservations
+            SET status = 'committed', updated_at = NOW()
+            WHERE order_id = NEW.id AND status = 'active' AND expires_at > NOW()
+            RETURNING product_id, variant_id, location_id, quantity
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM committed
+            GROUP BY product_id, variant_id, location_id
+        )
+        UPDATE inventory_levels il
+        SET available_quantity = il.available_quantity - t.quantity,
+            reserved_quantity = il.reserved_quantity + t.quantity,
+            updated_at = NOW()
+        FROM totals t
+        WHERE il.product_id = t.product_id
+          AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+          AND il.location_id = t.location_id;
+
+        FOR lapsed IN
+            SELECT r.id, r.product_id, r.variant_id, r.quantity, p.inventory_policy = 'continue' AS backorders
+            FROM stock_reservations r
+            JOIN products p ON p.id = r.product_id
+            WHERE r.order_id = NEW.id
+              AND (r.status = 'expired' OR (r.status = 'active' AND r.expires_at <= NOW()))
+            ORDER BY r.product_id, r.variant_id, r.id
+            FOR UPDATE OF r
+        LOOP
+            -- Lock the levels first; holds counted afterwards include every
+            -- reservation made by orders that held the lock before us
+            PERFORM 1
+            FROM inventory_levels l
+            WHERE l.product_id = lapsed.product_id AND l.variant_id IS NOT DISTINCT FROM lapsed.variant_id
+            ORDER BY l.location_id
+            FOR UPDATE;
+
+            best_location := NULL;
+            best_free := NULL;
+            SELECT l.location_id,
+                   l.available_quantity - COALESCE((
+                       SELECT SUM(h.quantity)
+                       FROM stock_reservations h
+                       WHERE h.product_id = l.product_id
+                         AND h.variant_id IS NOT DISTINCT FROM l.variant_id
+                         AND h.location_id = l.location_id
+                         AND h.status = 'active' AND h.expires_at > NOW()
+                   ), 0)
+            INTO best_location, best_free
+            FROM inventory_levels l
+            JOIN inventory_locations loc ON loc.id = l.location_id AND loc.is_active
+            WHERE l.product_id = lapsed.product_id AND l.variant_id IS NOT DISTINCT FROM lapsed.variant_id
+            ORDER BY 2 DESC, l.location_id
+            LIMIT 1;
+
+            IF best_location IS NOT NULL AND (best_free >= lapsed.quantity OR lapsed.backorders) THEN
+                UPDATE stock_reservations
+                SET status = 'committed', location_id = best_location, updated_at = NOW()
+                WHERE id = lapsed.id;
+                UPDATE inventory_levels
+                SET available_quantity = available_quantity - lapsed.quantity,
+                    reserved_quantity = reserved_quantity + lapsed.quantity,
+                    updated_at = NOW()
+                WHERE product_id = lapsed.product_id
+                  AND variant_id IS NOT DISTINCT FROM lapsed.variant_id
+                  AND location_id = best_location;
+            ELSE
+                UPDATE stock_reservations SET status = 'expired', updated_at = NOW() WHERE id = lapsed.id;
+                shortfalls := shortfalls || jsonb_build_object(
+                    'product_id', lapsed.product_id,
+                    'variant_id', lapsed.variant_id,
+                    'quantity', lapsed.quantity,
+                    'available', GREATEST(COALESCE(best_free, 0), 0)
+                );
+            END IF;
+        END LOOP;
+
+        IF jsonb_array_length(shortfalls) > 0 THEN
+            INSERT INTO order_events (order_id, event_type, payload)
+            VALUES (NEW.id, 'stock_shortfall', jsonb_build_object(
+                'reason', 'Stock reservation expired before payment',
+                'items', shortfalls
+            ));
+            UPDATE orders SET status = 'on_hold', updated_at = NOW()
+            WHERE id = NEW.id AND status IN ('pending', 'confirmed', 'processing');
+        END IF;
+    END IF;
+
+    IF NEW.status = 'cancelled' AND OLD.status IS DISTINCT FROM 'cancelled' THEN
+        WITH released AS (
+            UPDATE stock_reservations r
+            SET status = 'released'
+            FROM stock_reservations prior
+            WHERE prior.id = r.id AND r.order_id = NEW.id AND r.status IN ('active', 'expired', 'committed')
+            RETURNING r.product_id, r.variant_id, r.location_id, r.quantity, prior.status AS previous_status
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM released
+            WHERE previous_status = 'committed'
+            GROUP BY product_id, variant_id, location_id
+        )
+        UPDATE inventory_levels il
+        SET available_quantity = il.available_quantity + t.quantity,
+            reserved_quantity = GREATEST(il.reserved_quantity - t.quantity, 0),
+            updated_at = NOW()
+        FROM totals t
+        WHERE il.product_id = t.product_id
+          AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+          AND il.location_id = t.location_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
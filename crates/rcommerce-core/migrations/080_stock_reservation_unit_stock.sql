@@ -0,0 +1,175 @@
+-- ============================================================================
+-- Migration: Stock Reservation Unit Stock
+-- ============================================================================
+-- Stock levels are kept per location, but storefronts, feeds, marketplace
+-- sync and the stock event trigger (062) read the variant's or product's
+-- inventory_quantity. Committing a reservation when its order is paid now
+-- also takes the units from inventory_quantity, and releasing a committed
+-- reservation when the order is cancelled puts them back, so a sale shows
+-- up there like any other stock change.
+--
+-- Items without stock levels aren't reserved; InventoryService takes their
+-- stock from inventory_quantity when the order is created.
+-- ============================================================================
+
+-- Add (or, with a negative delta, take) units of the variant, or of the
+-- product when there is no variant
+CREATE OR REPLACE FUNCTION move_unit_stock(p_product_id UUID, p_variant_id UUID, p_delta INTEGER) RETURNS VOID AS $$
+BEGIN
+    IF p_variant_id IS NULL THEN
+        UPDATE products
+        SET inventory_quantity = inventory_quantity + p_delta, updated_at = NOW()
+        WHERE id = p_product_id AND inventory_management;
+    ELSE
+        UPDATE product_variants
+        SET inventory_quantity = inventory_quantity + p_delta, updated_at = NOW()
+        WHERE id = p_variant_id;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION move_order_reserved_stock() RETURNS TRIGGER AS $$
+DECLARE
+    lapsed RECORD;
+    best_location UUID;
+    best_free BIGINT;
+    shortfalls JSONB := '[]'::JSONB;
+BEGIN
+    IF NEW.payment_status = 'paid' AND OLD.payment_status IS DISTINCT FROM 'paid' THEN
+        WITH committed AS (
+            UPDATE stock_reservations
+            SET status = 'committed', updated_at = NOW()
+            WHERE order_id = NEW.id AND status = 'active' AND expires_at > NOW()
+            RETURNING product_id, variant_id, location_id, quantity
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM committed
+            GROUP BY product_id, variant_id, location_id
+        ), levels AS (
+            UPDATE inventory_levels il
+            SET available_quantity = il.available_quantity - t.quantity,
+                reserved_quantity = il.reserved_quantity + t.quantity,
+                updated_at = NOW()
+            FROM totals t
+            WHERE il.product_id = t.product_id
+              AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+              AND il.location_id = t.location_id
+            RETURNING il.id
+        ), variant_units AS (
+            UPDATE product_variants v
+            SET inventory_quantity = v.inventory_quantity - u.quantity, updated_at = NOW()
+            FROM (SELECT variant_id, SUM(quantity)::INTEGER AS quantity FROM totals WHERE variant_id IS NOT NULL GROUP BY variant_id) u
+            WHERE v.id = u.variant_id
+            RETURNING v.id
+        )
+        UPDATE products p
+        SET inventory_quantity = p.inventory_quantity - u.quantity, updated_at = NOW()
+        FROM (SELECT product_id, SUM(quantity)::INTEGER AS quantity FROM totals WHERE variant_id IS NULL GROUP BY product_id) u
+        WHERE p.id = u.product_id AND p.inventory_management;
+
+        FOR lapsed IN
+            SELECT r.id, r.product_id, r.variant_id, r.quantity, p.inventory_policy = 'continue' AS backorders
+            FROM stock_reservations r
+            JOIN products p ON p.id = r.product_id
+            WHERE r.order_id = NEW.id
+              AND (r.status = 'expired' OR (r.status = 'active' AND r.expires_at <= NOW()))
+            ORDER BY r.product_id, r.variant_id, r.id
+            FOR UPDATE OF r
+        LOOP
+            -- Lock the levels first; holds counted afterwards include every
+            -- reservation made by orders that held the lock before us
+            PERFORM 1
+            FROM inventory_levels l
+            WHERE l.product_id = lapsed.product_id AND l.variant_id IS NOT DISTINCT FROM lapsed.variant_id
+            ORDER BY l.location_id
+            FOR UPDATE;
+
+            best_location := NULL;
+            best_free := NULL;
+            SELECT l.location_id,
+                   l.available_quantity - COALESCE((
+                       SELECT SUM(h.quantity)
+                       FROM stock_reservations h
+                       WHERE h.product_id = l.product_id
+                         AND h.variant_id IS NOT DISTINCT FROM l.variant_id
+                         AND h.location_id = l.location_id
+                         AND h.status = 'active' AND h.expires_at > NOW()
+                   ), 0)
+            INTO best_location, best_free
+            FROM inventory_levels l
+            JOIN inventory_locations loc ON loc.id = l.location_id AND loc.is_active
+            WHERE l.product_id = lapsed.product_id AND l.variant_id IS NOT DISTINCT FROM lapsed.variant_id
+            ORDER BY 2 DESC, l.location_id
+            LIMIT 1;
+
+            IF best_location IS NOT NULL AND (best_free >= lapsed.quantity OR lapsed.backorders) THEN
+                UPDATE stock_reservations
+                SET status = 'committed', location_id = best_location, updated_at = NOW()
+                WHERE id = lapsed.id;
+                UPDATE inventory_levels
+                SET available_quantity = available_quantity - lapsed.quantity,
+                    reserved_quantity = reserved_quantity + lapsed.quantity,
+                    updated_at = NOW()
+                WHERE product_id = lapsed.product_id
+                  AND variant_id IS NOT DISTINCT FROM lapsed.variant_id
+                  AND location_id = best_location;
+                PERFORM move_unit_stock(lapsed.product_id, lapsed.variant_id, -lapsed.quantity);
+            ELSE
+                UPDATE stock_reservations SET status = 'expired', updated_at = NOW() WHERE id = lapsed.id;
+                shortfalls := shortfalls || jsonb_build_object(
+                    'product_id', lapsed.product_id,
+                    'variant_id', lapsed.variant_id,
+                    'quantity', lapsed.quantity,
+                    'available', GREATEST(COALESCE(best_free, 0), 0)
+                );
+            END IF;
+        END LOOP;
+
+        IF jsonb_array_length(shortfalls) > 0 THEN
+            INSERT INTO order_events (order_id, event_type, payload)
+            VALUES (NEW.id, 'stock_shortfall', jsonb_build_object(
+                'reason', 'Stock reservation expired before payment',
+                'items', shortfalls
+            ));
+            UPDATE orders SET status = 'on_hold', updated_at = NOW()
+            WHERE id = NEW.id AND status IN ('pending', 'confirmed', 'processing');
+        END IF;
+    END IF;
+
+    IF NEW.status = 'cancelled' AND OLD.status IS DISTINCT FROM 'cancelled' THEN
+        WITH released AS (
+            UPDATE stock_reservations r
+            SET status = 'released'
+            FROM stock_reservations prior
+            WHERE prior.id = r.id AND r.order_id = NEW.id AND r.status IN ('active', 'expired', 'committed')
+            RETURNING r.product_id, r.variant_id, r.location_id, r.quantity, prior.status AS previous_status
+        ), totals AS (
+            SELECT product_id, variant_id, location_id, SUM(quantity)::INTEGER AS quantity
+            FROM released
+            WHERE previous_status = 'committed'
+            GROUP BY product_id, variant_id, location_id
+        ), levels AS (
+            UPDATE inventory_levels il
+            SET available_quantity = il.available_quantity + t.quantity,
+                reserved_quantity = GREATEST(il.reserved_quantity - t.quantity, 0),
+                updated_at = NOW()
+            FROM totals t
+            WHERE il.product_id = t.product_id
+              AND il.variant_id IS NOT DISTINCT FROM t.variant_id
+              AND il.location_id = t.location_id
+            RETURNING il.id
+        ), variant_units AS (
+            UPDATE product_variants v
+            SET inventory_quantity = v.inventory_quantity + u.quantity, updated_at = NOW()
+            FROM (SELECT variant_id, SUM(quantity)::INTEGER AS quantity FROM totals WHERE variant_id IS NOT NULL GROUP BY variant_id) u
+            WHERE v.id = u.variant_id
+            RETURNING v.id
+        )
+        UPDATE products p
+        SET inventory_quantity = p.inventory_quantity + u.quantity, updated_at = NOW()
+        FROM (SELECT product_id, SUM(quantity)::INTEGER AS quantity FROM totals WHERE variant_id IS NULL GROUP BY product_id) u
+        WHERE p.id = u.product_id AND p.inventory_management;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
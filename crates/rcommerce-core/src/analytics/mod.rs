@@ -0,0 +1,188 @@
+//! First-party storefront analytics
+//!
+//! Storefronts post funnel events in batches ([`TrackEventsRequest`]);
+//! [`AnalyticsService::track`] drops them when analytics is off or the
+//! browser asked not to be tracked, samples sessions, hashes session IDs
+//! and stores what is left. [`AnalyticsService::funnel`] counts the
+//! sessions reaching each step of the checkout funnel, scaled up by the
+//! sample rate events were kept at. The `checkout_funnel` report sends the
+//! same counts on a schedule (see `crate::reports`).
+
+pub mod service;
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use service::{AnalyticsRetentionJob, AnalyticsService};
+
+/// A step of the checkout funnel, in funnel order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "storefront_event_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum StorefrontEventKind {
+    ProductViewed,
+    AddedToCart,
+    CheckoutStarted,
+    PaymentSubmitted,
+}
+
+impl StorefrontEventKind {
+    /// Every step, in funnel order
+    pub const ALL: [StorefrontEventKind; 4] = [
+        StorefrontEventKind::ProductViewed,
+        StorefrontEventKind::AddedToCart,
+        StorefrontEventKind::CheckoutStarted,
+        StorefrontEventKind::PaymentSubmitted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorefrontEventKind::ProductViewed => "product_viewed",
+            StorefrontEventKind::AddedToCart => "added_to_cart",
+            StorefrontEventKind::CheckoutStarted => "checkout_started",
+            StorefrontEventKind::PaymentSubmitted => "payment_submitted",
+        }
+    }
+}
+
+impl fmt::Display for StorefrontEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An event as posted by a storefront
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackEvent {
+    pub event: StorefrontEventKind,
+    /// The storefront's own visitor session ID; stored only as a hash
+    pub session_id: String,
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub cart_id: Option<Uuid>,
+    pub quantity: Option<i32>,
+    /// When the event happened in the browser; defaults to when it arrives
+    pub occurred_at: Option<DateTime<Utc>>,
+}
+
+/// A batch of events
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackEventsRequest {
+    pub events: Vec<TrackEvent>,
+}
+
+/// What happened to a batch of events
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TrackEventsResult {
+    /// Events stored
+    pub accepted: usize,
+    /// Events of sessions left out by sampling
+    pub sampled_out: usize,
+    /// Events dropped: analytics off, do-not-track, or invalid
+    pub dropped: usize,
+}
+
+/// An event ready to store
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewStorefrontEvent {
+    pub event: StorefrontEventKind,
+    pub session_hash: String,
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub cart_id: Option<Uuid>,
+    pub quantity: Option<i32>,
+    /// 1 / the sample rate the event was kept at
+    pub weight: f64,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Sessions reaching one step of the funnel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunnelStep {
+    pub event: StorefrontEventKind,
+    /// Estimated sessions, scaled up by the sample rate
+    pub sessions: i64,
+    /// Percentage of the previous step's sessions; None for the first step
+    pub conversion_from_previous: Option<f64>,
+    /// Percentage of the first step's sessions
+    pub conversion_from_start: Option<f64>,
+}
+
+/// The checkout funnel over a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunnelReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub steps: Vec<FunnelStep>,
+}
+
+impl FunnelReport {
+    /// Build the funnel from estimated sessions per step; missing steps are zero
+    pub fn new(from: DateTime<Utc>, to: DateTime<Utc>, sessions: &[(StorefrontEventKind, f64)]) -> Self {
+        let count = |event: StorefrontEventKind| {
+            sessions
+                .iter()
+                .find(|(kind, _)| *kind == event)
+                .map_or(0, |(_, sessions)| sessions.round() as i64)
+        };
+        let percentage = |part: i64, whole: i64| {
+            (whole > 0).then(|| (part as f64 * 1000.0 / whole as f64).round() / 10.0)
+        };
+
+        let first = count(StorefrontEventKind::ALL[0]);
+        let mut previous: Option<i64> = None;
+        let steps = StorefrontEventKind::ALL
+            .iter()
+            .map(|&event| {
+                let sessions = count(event);
+                let step = FunnelStep {
+                    event,
+                    sessions,
+                    conversion_from_previous: previous.and_then(|previous| percentage(sessions, previous)),
+                    conversion_from_start: percentage(sessions, first),
+                };
+                previous = Some(sessions);
+                step
+            })
+            .collect();
+        Self { from, to, steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_funnel_report() {
+        let now = Utc::now();
+        let report = FunnelReport::new(
+            now,
+            now,
+            &[
+                (StorefrontEventKind::AddedToCart, 250.0),
+                (StorefrontEventKind::ProductViewed, 1000.0),
+                (StorefrontEventKind::PaymentSubmitted, 49.6),
+            ],
+        );
+
+        let steps: Vec<_> = report.steps.iter().map(|s| (s.event, s.sessions)).collect();
+        assert_eq!(
+            steps,
+            vec![
+                (StorefrontEventKind::ProductViewed, 1000),
+                (StorefrontEventKind::AddedToCart, 250),
+                (StorefrontEventKind::CheckoutStarted, 0),
+                (StorefrontEventKind::PaymentSubmitted, 50),
+            ]
+        );
+        assert_eq!(report.steps[0].conversion_from_previous, None);
+        assert_eq!(report.steps[1].conversion_from_previous, Some(25.0));
+        // No sessions at the previous step
+        assert_eq!(report.steps[3].conversion_from_previous, None);
+        assert_eq!(report.steps[3].conversion_from_start, Some(5.0));
+    }
+}
@@ -0,0 +1,181 @@
+//! Storefront event ingestion, the checkout funnel and event retention
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+
+use super::{FunnelReport, NewStorefrontEvent, TrackEvent, TrackEventsRequest, TrackEventsResult};
+use crate::config::AnalyticsConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::repository::AnalyticsRepository;
+use crate::{Error, Result};
+
+/// Events older than this when they arrive are dropped
+const MAX_EVENT_AGE_HOURS: i64 = 24;
+
+/// Longest session ID accepted
+const MAX_SESSION_ID_LEN: usize = 128;
+
+/// Storefront analytics service
+pub struct AnalyticsService<R: AnalyticsRepository> {
+    repository: R,
+    config: AnalyticsConfig,
+}
+
+impl<R: AnalyticsRepository> AnalyticsService<R> {
+    pub fn new(repository: R, config: AnalyticsConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn config(&self) -> &AnalyticsConfig {
+        &self.config
+    }
+
+    /// Store a batch of events; `do_not_track` is set when the browser sent
+    /// `DNT: 1` or `Sec-GPC: 1`
+    pub async fn track(&self, request: TrackEventsRequest, do_not_track: bool) -> Result<TrackEventsResult> {
+        if request.events.len() > self.config.max_batch_size {
+            return Err(Error::validation(format!(
+                "At most {} events can be sent at once",
+                self.config.max_batch_size
+            )));
+        }
+
+        let mut result = TrackEventsResult::default();
+        if !self.config.enabled || (self.config.honor_do_not_track && do_not_track) {
+            result.dropped = request.events.len();
+            return Ok(result);
+        }
+
+        let now = Utc::now();
+        let mut events = Vec::with_capacity(request.events.len());
+        for event in request.events {
+            match self.prepare(event, now) {
+                Some(event) if is_sampled(&event.session_hash, self.config.sample_rate) => events.push(event),
+                Some(_) => result.sampled_out += 1,
+                None => result.dropped += 1,
+            }
+        }
+
+        if !events.is_empty() {
+            self.repository.insert_events(&events).await?;
+        }
+        result.accepted = events.len();
+        Ok(result)
+    }
+
+    /// Sessions reaching each step of the funnel between two times
+    pub async fn funnel(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<FunnelReport> {
+        if from >= to {
+            return Err(Error::validation("from must be before to"));
+        }
+        let sessions = self.repository.funnel_sessions(from, to).await?;
+        Ok(FunnelReport::new(from, to, &sessions))
+    }
+
+    /// Drop events older than `retention_days`; returns how many
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let cutoff = Utc::now() - Duration::days(i64::from(self.config.retention_days));
+        self.repository.purge_before(cutoff).await
+    }
+
+    /// An event ready to store, or None if it is invalid or too old
+    fn prepare(&self, event: TrackEvent, now: DateTime<Utc>) -> Option<NewStorefrontEvent> {
+        let session_id = event.session_id.trim();
+        if session_id.is_empty() || session_id.len() > MAX_SESSION_ID_LEN {
+            return None;
+        }
+        if event.quantity.is_some_and(|quantity| !(1..=10_000).contains(&quantity)) {
+            return None;
+        }
+        // Browser clocks drift: events from the future count as now
+        let occurred_at = event.occurred_at.unwrap_or(now).min(now);
+        if occurred_at < now - Duration::hours(MAX_EVENT_AGE_HOURS) {
+            return None;
+        }
+
+        Some(NewStorefrontEvent {
+            event: event.event,
+            session_hash: session_hash(&self.config.session_salt, session_id),
+            product_id: event.product_id,
+            variant_id: event.variant_id,
+            cart_id: event.cart_id,
+            quantity: event.quantity,
+            weight: 1.0 / self.config.sample_rate,
+            occurred_at,
+        })
+    }
+}
+
+/// Salted SHA-256 of a session ID, hex encoded
+fn session_hash(salt: &str, session_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b":");
+    hasher.update(session_id.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Whether a session is kept at a sample rate; all events of a session
+/// are kept or none are, so funnels stay whole
+fn is_sampled(session_hash: &str, sample_rate: f64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    let bucket = u32::from_str_radix(&session_hash[..8], 16).unwrap_or(0);
+    f64::from(bucket) / f64::from(u32::MAX) < sample_rate
+}
+
+/// The `analytics_retention` recurring job
+pub struct AnalyticsRetentionJob<R: AnalyticsRepository> {
+    analytics: Arc<AnalyticsService<R>>,
+}
+
+impl<R: AnalyticsRepository> AnalyticsRetentionJob<R> {
+    pub fn new(analytics: Arc<AnalyticsService<R>>) -> Self {
+        Self { analytics }
+    }
+}
+
+#[async_trait]
+impl<R: AnalyticsRepository + 'static> RecurringJob for AnalyticsRetentionJob<R> {
+    fn name(&self) -> &str {
+        "analytics_retention"
+    }
+
+    fn description(&self) -> &str {
+        "Drop storefront analytics events past their retention period"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.analytics.config().purge_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(format!("purged {} events", self.analytics.purge_expired().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_hash_is_salted() {
+        let hash = session_hash("salt", "visitor-1");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, session_hash("salt", "visitor-1"));
+        assert_ne!(hash, session_hash("other", "visitor-1"));
+    }
+
+    #[test]
+    fn test_sampling_keeps_about_the_rate() {
+        let kept = (0..10_000)
+            .filter(|i| is_sampled(&session_hash("", &i.to_string()), 0.25))
+            .count();
+        assert!((2_000..3_000).contains(&kept), "kept {}", kept);
+        assert!(is_sampled(&session_hash("", "any"), 1.0));
+    }
+}
@@ -0,0 +1,162 @@
+//! Rule actions
+//!
+//! Actions run in order once a rule's conditions match:
+//!
+//! ```json
+//! [
+//!     {"type": "add_tag", "tag": "priority"},
+//!     {"type": "notify_slack", "message": "Order {{order.order_number}} ({{order.total}} {{order.currency}}) is priority"}
+//! ]
+//! ```
+//!
+//! Slack messages may reference context fields as `{{path}}`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::conditions::lookup;
+use crate::models::AutomationTrigger;
+use crate::{Error, Result};
+
+/// Actions per rule at most
+pub const MAX_ACTIONS: usize = 20;
+
+/// Longest tag an action may add
+const MAX_TAG_LEN: usize = 100;
+
+/// What a matching rule does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationAction {
+    /// Add a tag to the order
+    AddTag { tag: String },
+    /// Remove a tag from the order
+    RemoveTag { tag: String },
+    /// Put the order on hold (pending, confirmed and processing orders only)
+    HoldOrder {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// Post a message to a Slack incoming webhook
+    NotifySlack {
+        message: String,
+        /// Defaults to `automation.slack_webhook_url`
+        #[serde(default)]
+        webhook_url: Option<String>,
+    },
+}
+
+impl AutomationAction {
+    /// Whether the action changes an order
+    pub fn needs_order(&self) -> bool {
+        !matches!(self, AutomationAction::NotifySlack { .. })
+    }
+
+    /// Check the action can run on the trigger's events
+    pub fn validate(&self, trigger: AutomationTrigger, default_slack_webhook: bool) -> Result<()> {
+        if self.needs_order() && !trigger.has_order() {
+            return Err(Error::validation(format!("{} events have no order to change", trigger)));
+        }
+        match self {
+            AutomationAction::AddTag { tag } | AutomationAction::RemoveTag { tag } => {
+                let tag = tag.trim();
+                if tag.is_empty() || tag.chars().count() > MAX_TAG_LEN {
+                    return Err(Error::validation(format!(
+                        "Tags must have 1 to {} characters",
+                        MAX_TAG_LEN
+                    )));
+                }
+            }
+            AutomationAction::HoldOrder { .. } => {}
+            AutomationAction::NotifySlack { message, webhook_url } => {
+                if message.trim().is_empty() {
+                    return Err(Error::validation("Slack messages must not be empty"));
+                }
+                match webhook_url {
+                    Some(url) if !url.starts_with("https://") => {
+                        return Err(Error::validation("Slack webhook URLs must use https"));
+                    }
+                    None if !default_slack_webhook => {
+                        return Err(Error::validation(
+                            "Give the Slack action a webhook_url or set automation.slack_webhook_url",
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// What the action would do in the context
+    pub fn describe(&self, context: &Value) -> String {
+        match self {
+            AutomationAction::AddTag { tag } => format!("Add tag '{}'", tag.trim()),
+            AutomationAction::RemoveTag { tag } => format!("Remove tag '{}'", tag.trim()),
+            AutomationAction::HoldOrder { reason: Some(reason) } => format!("Put the order on hold: {}", reason),
+            AutomationAction::HoldOrder { reason: None } => "Put the order on hold".to_string(),
+            AutomationAction::NotifySlack { message, .. } => format!("Notify Slack: {}", render(message, context)),
+        }
+    }
+}
+
+/// Replace `{{path}}` placeholders with context fields (missing ones are empty)
+pub fn render(template: &str, context: &Value) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let path = rest[start + 2..start + end].trim();
+        match lookup(context, path) {
+            Some(Value::String(text)) => rendered.push_str(text),
+            Some(Value::Null) | None => {}
+            Some(Value::Array(values)) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()))
+                    .collect();
+                rendered.push_str(&values.join(", "));
+            }
+            Some(value) => rendered.push_str(&value.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let context = json!({"order": {"order_number": "ORD-1001", "total": 612.4, "tags": ["a", "b"]}});
+        assert_eq!(
+            render("Order {{order.order_number}} ({{ order.total }}) [{{order.tags}}]{{order.nope}}", &context),
+            "Order ORD-1001 (612.4) [a, b]"
+        );
+        assert_eq!(render("Unclosed {{order", &context), "Unclosed {{order");
+    }
+
+    #[test]
+    fn test_validate() {
+        let tag: AutomationAction = serde_json::from_value(json!({"type": "add_tag", "tag": "priority"})).unwrap();
+        assert!(tag.validate(AutomationTrigger::OrderPaid, false).is_ok());
+        assert!(tag.validate(AutomationTrigger::CustomerCreated, false).is_err());
+
+        let slack = AutomationAction::NotifySlack { message: "New VIP".to_string(), webhook_url: None };
+        assert!(slack.validate(AutomationTrigger::CustomerCreated, true).is_ok());
+        assert!(slack.validate(AutomationTrigger::CustomerCreated, false).is_err());
+
+        let insecure = AutomationAction::NotifySlack {
+            message: "New VIP".to_string(),
+            webhook_url: Some("http://hooks.slack.com/services/x".to_string()),
+        };
+        assert!(insecure.validate(AutomationTrigger::OrderPaid, true).is_err());
+    }
+}
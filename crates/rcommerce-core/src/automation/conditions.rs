@@ -0,0 +1,324 @@
+//! Rule conditions
+//!
+//! Conditions are a JSON tree of `all`, `any` and `not` groups around
+//! comparisons of a context field with a value:
+//!
+//! ```json
+//! {"all": [
+//!     {"field": "order.total", "op": "gt", "value": 500},
+//!     {"field": "order.shipping_country", "op": "in", "value": ["DE", "AT"]}
+//! ]}
+//! ```
+//!
+//! Fields are dot paths into the rule context (see [`super`]). Numbers
+//! compare numerically whether the context holds them as numbers or
+//! strings; strings compare case-insensitively.
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::AutomationTrigger;
+use crate::{Error, Result};
+
+/// Groups nested at most
+const MAX_DEPTH: usize = 8;
+
+/// Comparisons per rule at most
+const MAX_COMPARISONS: usize = 50;
+
+/// Comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// Value is a list containing the field
+    In,
+    NotIn,
+    /// Field is a list containing the value, or a string containing it
+    Contains,
+    /// Field is set (`"value": false` for unset)
+    Exists,
+}
+
+/// A rule's condition tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Condition {
+    /// Every condition matches (an empty list always matches)
+    All { all: Vec<Condition> },
+    /// At least one condition matches
+    Any { any: Vec<Condition> },
+    Not { not: Box<Condition> },
+    Compare {
+        field: String,
+        op: Operator,
+        #[serde(default)]
+        value: Value,
+    },
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Condition::All { all: Vec::new() }
+    }
+}
+
+/// One comparison of a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionTrace {
+    pub field: String,
+    pub op: Operator,
+    pub value: Value,
+    /// The field's value in the context (null if missing)
+    pub actual: Value,
+    pub matched: bool,
+}
+
+impl Condition {
+    /// Whether the context matches
+    pub fn matches(&self, context: &Value) -> bool {
+        self.evaluate(context, &mut Vec::new())
+    }
+
+    /// Whether the context matches, recording every comparison; groups
+    /// don't short-circuit so the trace covers the whole tree
+    pub fn evaluate(&self, context: &Value, trace: &mut Vec<ConditionTrace>) -> bool {
+        match self {
+            Condition::All { all } => {
+                let matched: Vec<bool> = all.iter().map(|condition| condition.evaluate(context, trace)).collect();
+                matched.into_iter().all(|matched| matched)
+            }
+            Condition::Any { any } => {
+                let matched: Vec<bool> = any.iter().map(|condition| condition.evaluate(context, trace)).collect();
+                matched.into_iter().any(|matched| matched)
+            }
+            Condition::Not { not } => !not.evaluate(context, trace),
+            Condition::Compare { field, op, value } => {
+                let actual = lookup(context, field);
+                let matched = compare(actual, *op, value);
+                trace.push(ConditionTrace {
+                    field: field.clone(),
+                    op: *op,
+                    value: value.clone(),
+                    actual: actual.cloned().unwrap_or(Value::Null),
+                    matched,
+                });
+                matched
+            }
+        }
+    }
+
+    /// Check fields and values against what the trigger's context holds
+    pub fn validate(&self, trigger: AutomationTrigger) -> Result<()> {
+        let mut comparisons = 0;
+        self.validate_at(trigger, 1, &mut comparisons)?;
+        if comparisons > MAX_COMPARISONS {
+            return Err(Error::validation(format!(
+                "Conditions may have at most {} comparisons",
+                MAX_COMPARISONS
+            )));
+        }
+        Ok(())
+    }
+
+    fn validate_at(&self, trigger: AutomationTrigger, depth: usize, comparisons: &mut usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(Error::validation(format!("Conditions may nest at most {} levels", MAX_DEPTH)));
+        }
+        match self {
+            Condition::All { all: conditions } | Condition::Any { any: conditions } => conditions
+                .iter()
+                .try_for_each(|condition| condition.validate_at(trigger, depth + 1, comparisons)),
+            Condition::Not { not } => not.validate_at(trigger, depth + 1, comparisons),
+            Condition::Compare { field, op, value } => {
+                *comparisons += 1;
+                validate_field(trigger, field)?;
+                validate_value(field, *op, value)
+            }
+        }
+    }
+}
+
+/// Context roots a field may start with
+fn validate_field(trigger: AutomationTrigger, field: &str) -> Result<()> {
+    let root = field.split('.').next().unwrap_or_default();
+    let allowed = match root {
+        "trigger" => field == "trigger",
+        "event" | "customer" => field.len() > root.len() + 1,
+        "order" => trigger.has_order() && field.len() > root.len() + 1,
+        _ => false,
+    };
+    if !allowed {
+        let roots = if trigger.has_order() { "order., customer., event. or trigger" } else { "customer., event. or trigger" };
+        return Err(Error::validation(format!(
+            "Unknown field '{}' for {}; fields start with {}",
+            field, trigger, roots
+        )));
+    }
+    Ok(())
+}
+
+fn validate_value(field: &str, op: Operator, value: &Value) -> Result<()> {
+    let valid = match op {
+        Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte => as_decimal(value).is_some(),
+        Operator::In | Operator::NotIn => value.as_array().is_some_and(|values| !values.is_empty()),
+        Operator::Exists => value.is_null() || value.is_boolean(),
+        Operator::Eq | Operator::Ne | Operator::Contains => !value.is_null() && !value.is_object(),
+    };
+    if !valid {
+        let expected = match op {
+            Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte => "a number",
+            Operator::In | Operator::NotIn => "a non-empty list",
+            Operator::Exists => "true, false or nothing",
+            Operator::Eq | Operator::Ne | Operator::Contains => "a string, number, boolean or list",
+        };
+        return Err(Error::validation(format!(
+            "Condition on '{}' needs {} as its value",
+            field, expected
+        )));
+    }
+    Ok(())
+}
+
+/// The value at a dot path, if present
+pub fn lookup<'a>(context: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(context, |value, key| value.get(key))
+}
+
+fn compare(actual: Option<&Value>, op: Operator, expected: &Value) -> bool {
+    let actual = actual.filter(|value| !value.is_null());
+    match op {
+        Operator::Exists => actual.is_some() == expected.as_bool().unwrap_or(true),
+        Operator::Eq => actual.is_some_and(|actual| equals(actual, expected)),
+        Operator::Ne => !actual.is_some_and(|actual| equals(actual, expected)),
+        Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte => {
+            match (actual.and_then(as_decimal), as_decimal(expected)) {
+                (Some(actual), Some(expected)) => match op {
+                    Operator::Gt => actual > expected,
+                    Operator::Gte => actual >= expected,
+                    Operator::Lt => actual < expected,
+                    _ => actual <= expected,
+                },
+                _ => false,
+            }
+        }
+        Operator::In => actual.is_some_and(|actual| is_in(actual, expected)),
+        Operator::NotIn => !actual.is_some_and(|actual| is_in(actual, expected)),
+        Operator::Contains => match actual {
+            Some(Value::Array(values)) => values.iter().any(|value| equals(value, expected)),
+            Some(Value::String(text)) => expected
+                .as_str()
+                .is_some_and(|needle| text.to_lowercase().contains(&needle.to_lowercase())),
+            _ => false,
+        },
+    }
+}
+
+fn is_in(actual: &Value, expected: &Value) -> bool {
+    expected
+        .as_array()
+        .is_some_and(|values| values.iter().any(|value| equals(actual, value)))
+}
+
+fn equals(actual: &Value, expected: &Value) -> bool {
+    if let (Some(actual), Some(expected)) = (as_decimal(actual), as_decimal(expected)) {
+        return actual == expected;
+    }
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) => actual.eq_ignore_ascii_case(expected),
+        _ => actual == expected,
+    }
+}
+
+/// A number, or a string holding one
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(number) => {
+            let text = number.to_string();
+            Decimal::from_str(&text).or_else(|_| Decimal::from_scientific(&text)).ok()
+        }
+        Value::String(text) => Decimal::from_str(text.trim()).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> Value {
+        json!({
+            "trigger": "order_paid",
+            "order": {
+                "total": "612.40",
+                "shipping_country": "DE",
+                "tags": ["wholesale"],
+                "coupon_code": null
+            },
+            "customer": { "order_count": 3 }
+        })
+    }
+
+    fn parse(condition: Value) -> Condition {
+        serde_json::from_value(condition).unwrap()
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let context = context();
+        let matches = |condition: Value| parse(condition).matches(&context);
+
+        assert!(matches(json!({"field": "order.total", "op": "gt", "value": 500})));
+        assert!(!matches(json!({"field": "order.total", "op": "lte", "value": "500"})));
+        assert!(matches(json!({"field": "order.shipping_country", "op": "eq", "value": "de"})));
+        assert!(matches(json!({"field": "order.shipping_country", "op": "in", "value": ["AT", "DE"]})));
+        assert!(matches(json!({"field": "order.tags", "op": "contains", "value": "Wholesale"})));
+        assert!(matches(json!({"field": "customer.order_count", "op": "eq", "value": 3})));
+        assert!(matches(json!({"field": "order.coupon_code", "op": "exists", "value": false})));
+        assert!(matches(json!({"field": "order.missing", "op": "ne", "value": "x"})));
+        assert!(!matches(json!({"field": "order.missing", "op": "gt", "value": 0})));
+    }
+
+    #[test]
+    fn test_groups_and_trace() {
+        let condition = parse(json!({"all": [
+            {"field": "order.total", "op": "gt", "value": 500},
+            {"any": [
+                {"field": "order.shipping_country", "op": "eq", "value": "FR"},
+                {"not": {"field": "order.tags", "op": "contains", "value": "wholesale"}}
+            ]}
+        ]}));
+
+        let mut trace = Vec::new();
+        assert!(!condition.evaluate(&context(), &mut trace));
+        // Every comparison is traced, even after the outcome is known
+        assert_eq!(trace.len(), 3);
+        assert!(trace[0].matched);
+        assert_eq!(trace[1].actual, json!("DE"));
+        assert!(Condition::default().matches(&context()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = parse(json!({"all": [{"field": "order.total", "op": "gt", "value": 500}]}));
+        assert!(valid.validate(AutomationTrigger::OrderCreated).is_ok());
+        // Customer events have no order
+        assert!(valid.validate(AutomationTrigger::CustomerCreated).is_err());
+
+        let not_a_number = parse(json!({"field": "order.total", "op": "gt", "value": "lots"}));
+        assert!(not_a_number.validate(AutomationTrigger::OrderPaid).is_err());
+        let unknown_root = parse(json!({"field": "cart.total", "op": "eq", "value": 1}));
+        assert!(unknown_root.validate(AutomationTrigger::OrderPaid).is_err());
+        let empty_list = parse(json!({"field": "order.status", "op": "in", "value": []}));
+        assert!(empty_list.validate(AutomationTrigger::OrderPaid).is_err());
+    }
+}
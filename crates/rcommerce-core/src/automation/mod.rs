@@ -0,0 +1,25 @@
+//! Automation rules
+//!
+//! "When an order over 500 ships to DE, tag it `priority` and tell Slack":
+//! a rule has a trigger, a condition tree ([`Condition`]) and actions
+//! ([`AutomationAction`]), all stored in `automation_rules`. Database
+//! triggers queue events for triggers an enabled rule listens to, and the
+//! `automation` recurring job runs them ([`AutomationEngine`]).
+//!
+//! Rules are evaluated against a JSON context:
+//!
+//! - `trigger` - the trigger name
+//! - `event` - trigger details (`old_status`, `new_status` for status changes)
+//! - `order` - `order_number`, `status`, `payment_status`, `total`,
+//!   `currency`, `channel`, `item_count`, `skus`, `tags`, `coupon_code`,
+//!   `shipping_country`, `billing_country`, ... (order triggers only)
+//! - `customer` - `email`, `order_count`, `total_spent`, `accepts_marketing`,
+//!   ... (null for guest orders)
+
+pub mod actions;
+pub mod conditions;
+pub mod engine;
+
+pub use actions::AutomationAction;
+pub use conditions::{Condition, ConditionTrace, Operator};
+pub use engine::{AutomationEngine, AutomationJob, AutomationReport};
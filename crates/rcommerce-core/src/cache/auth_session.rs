@@ -0,0 +1,195 @@
+//! Server-side storefront sessions
+//!
+//! Backs cookie authentication (see `[sessions]`). The cookie holds a
+//! random token; Redis holds the session under the token's SHA-256, so a
+//! leaked Redis dump cannot be replayed as cookies. Sessions slide by
+//! `ttl_secs` on use and end `max_age_secs` after login.
+//!
+//! Keys:
+//! - `session:<sha256 of token>` - the session
+//! - `session:customer:<id>`     - set of a customer's session hashes, to
+//!   end them all (password reset, logout everywhere)
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::cache::{CacheError, CacheNamespace, CacheResult, RedisPool};
+use crate::config::SessionConfig;
+
+/// A logged-in storefront session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub customer_id: Uuid,
+    pub email: String,
+    /// Permissions at login, as in an access token
+    pub permissions: Vec<String>,
+    /// Must be echoed in the CSRF header on unsafe requests
+    pub csrf_token: String,
+    /// Unix timestamps
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl AuthSession {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Whether `provided` matches the session's CSRF token
+    pub fn csrf_matches(&self, provided: &str) -> bool {
+        constant_time_eq(self.csrf_token.as_bytes(), provided.as_bytes())
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// A random 256-bit token, hex encoded
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn session_key(hash: &str) -> String {
+    CacheNamespace::Session.key(hash)
+}
+
+fn customer_key(customer_id: Uuid) -> String {
+    CacheNamespace::Session.key(format!("customer:{}", customer_id))
+}
+
+/// Session storage in Redis
+#[derive(Clone)]
+pub struct AuthSessionStore {
+    pool: RedisPool,
+    config: SessionConfig,
+}
+
+impl AuthSessionStore {
+    /// Create a new session store
+    pub fn new(pool: RedisPool, config: SessionConfig) -> Self {
+        Self { pool, config }
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    fn ttl(&self, session: &AuthSession, now: i64) -> u64 {
+        let remaining = (session.expires_at - now).max(1) as u64;
+        self.config.ttl_secs.min(remaining)
+    }
+
+    /// Start a session; returns the cookie token and the session
+    pub async fn create(&self, customer_id: Uuid, email: &str, permissions: Vec<String>) -> CacheResult<(String, AuthSession)> {
+        let now = chrono::Utc::now().timestamp();
+        let session = AuthSession {
+            customer_id,
+            email: email.to_string(),
+            permissions,
+            csrf_token: new_token(),
+            created_at: now,
+            expires_at: now + self.config.max_age_secs as i64,
+        };
+        let token = new_token();
+        let hash = token_hash(&token);
+        let data = serde_json::to_vec(&session).map_err(|e| CacheError::SerializationError(e.to_string()))?;
+
+        let conn = self.pool.get().await?;
+        conn.setex(&session_key(&hash), self.ttl(&session, now), &data).await?;
+        let customer_key = customer_key(customer_id);
+        conn.sadd(&customer_key, &hash).await?;
+        conn.expire(&customer_key, self.config.max_age_secs).await?;
+
+        Ok((token, session))
+    }
+
+    /// Load the session for a cookie token and extend it
+    pub async fn load(&self, token: &str) -> CacheResult<Option<AuthSession>> {
+        let hash = token_hash(token);
+        let key = session_key(&hash);
+        let conn = self.pool.get().await?;
+        let Some(data) = conn.get(&key).await? else {
+            return Ok(None);
+        };
+        let session: AuthSession =
+            serde_json::from_slice(&data).map_err(|e| CacheError::DeserializationError(e.to_string()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        if session.is_expired(now) {
+            conn.del(&key).await?;
+            conn.srem(&customer_key(session.customer_id), &hash).await?;
+            return Ok(None);
+        }
+        conn.expire(&key, self.ttl(&session, now)).await?;
+        Ok(Some(session))
+    }
+
+    /// End the session for a cookie token
+    pub async fn destroy(&self, token: &str, customer_id: Uuid) -> CacheResult<bool> {
+        let hash = token_hash(token);
+        let conn = self.pool.get().await?;
+        conn.srem(&customer_key(customer_id), &hash).await?;
+        conn.del(&session_key(&hash)).await
+    }
+
+    /// End every session of a customer; returns how many were ended
+    pub async fn destroy_all(&self, customer_id: Uuid) -> CacheResult<usize> {
+        let conn = self.pool.get().await?;
+        let customer_key = customer_key(customer_id);
+        let mut ended = 0;
+        for hash in conn.smembers(&customer_key).await? {
+            if conn.del(&session_key(&hash)).await? {
+                ended += 1;
+            }
+        }
+        conn.del(&customer_key).await?;
+        Ok(ended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_and_keys() {
+        let token = new_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, new_token());
+
+        // The raw token never appears in a key
+        let key = session_key(&token_hash(&token));
+        assert!(key.starts_with("session:"));
+        assert!(!key.contains(&token));
+
+        let customer_id = Uuid::nil();
+        assert_eq!(customer_key(customer_id), format!("session:customer:{}", customer_id));
+    }
+
+    #[test]
+    fn test_session_checks() {
+        let session = AuthSession {
+            customer_id: Uuid::new_v4(),
+            email: "a@example.com".to_string(),
+            permissions: vec!["orders:read".to_string()],
+            csrf_token: "abc123".to_string(),
+            created_at: 1_000,
+            expires_at: 2_000,
+        };
+        assert!(session.csrf_matches("abc123"));
+        assert!(!session.csrf_matches("abc124"));
+        assert!(!session.csrf_matches("abc1234"));
+        assert!(!session.csrf_matches(""));
+        assert!(!session.is_expired(1_999));
+        assert!(session.is_expired(2_000));
+    }
+}
@@ -37,6 +37,14 @@ impl RedisPool {
         
         Ok(RedisConnection::new(conn))
     }
+
+    /// Open a dedicated connection for SUBSCRIBE
+    pub async fn pubsub(&self) -> CacheResult<redis::aio::PubSub> {
+        self.client
+            .get_async_pubsub()
+            .await
+            .map_err(|e| CacheError::ConnectionError(e.to_string()))
+    }
 }
 
 /// Single Redis connection
@@ -178,6 +186,13 @@ impl RedisConnection {
         }
     }
     
+    /// Run a Lua script atomically
+    pub async fn eval(&self, script: &str, keys: &[String], args: &[String]) -> CacheResult<Value> {
+        let mut cmd = Cmd::new();
+        cmd.arg("EVAL").arg(script).arg(keys.len()).arg(keys).arg(args);
+        self.execute(cmd).await
+    }
+    
     /// Execute a pipeline and return results
     pub async fn execute_pipeline(&self, pipeline: &Pipeline) -> CacheResult<Vec<Value>> {
         let mut conn = self.inner.lock().await;
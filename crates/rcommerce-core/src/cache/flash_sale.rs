@@ -0,0 +1,513 @@
+//! Flash sale counters and waiting rooms
+//!
+//! While a flash sale is live its stock counter, purchase tokens, waiting
+//! queue and per-customer totals live in Redis, so a rush of buyers is
+//! admitted, queued and turned away without touching Postgres. Every
+//! check-and-update runs as one Lua script, which keeps the counters exact
+//! under any amount of concurrency. All keys of a sale share a hash tag and
+//! land on one cluster slot.
+//!
+//! Keys (`flash:sale:{<id>}:...`):
+//! - `meta`    - hash of the sale's window and limits; its absence means the sale is not live
+//! - `stock`   - units left
+//! - `tokens`  - sorted set of purchase tokens by expiry (ms)
+//! - `owners`  - hash of token -> customer
+//! - `holders` - hash of customer -> token
+//! - `queue`   - sorted set of waiting customers by arrival (ms)
+//! - `seen`    - sorted set of waiting customers by last poll (ms)
+//! - `bought`  - hash of customer -> units bought
+//!
+//! `flash:sale:item:<product>:<variant|any>` maps a product to its live sale.
+
+use chrono::{DateTime, TimeZone, Utc};
+use redis::Value;
+use uuid::Uuid;
+
+use crate::cache::{CacheError, CacheNamespace, CacheResult, RedisPool};
+use crate::config::FlashSalesConfig;
+use crate::models::{FlashSale, FlashSaleStats};
+
+/// Keys are kept this long after a sale ends, in case it is closed late
+const KEY_GRACE_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Purge expired tokens and stale waiters, then admit, queue or turn away a customer
+///
+/// KEYS: the sale's keys in `SaleKeys::all` order
+/// ARGV: customer, now (ms), new token, token TTL (ms), queue timeout (ms)
+const ENTER_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then return {'closed'} end
+local now = tonumber(ARGV[2])
+local meta = redis.call('HMGET', KEYS[1], 'starts_at', 'ends_at', 'per_customer_limit', 'max_active_tokens', 'expire_at')
+if now < tonumber(meta[1]) then return {'not_started', meta[1]} end
+if now >= tonumber(meta[2]) then return {'ended'} end
+
+for _, token in ipairs(redis.call('ZRANGEBYSCORE', KEYS[3], '-inf', now)) do
+    local owner = redis.call('HGET', KEYS[4], token)
+    redis.call('HDEL', KEYS[4], token)
+    if owner then redis.call('HDEL', KEYS[5], owner) end
+end
+redis.call('ZREMRANGEBYSCORE', KEYS[3], '-inf', now)
+local stale = now - tonumber(ARGV[5])
+for _, waiter in ipairs(redis.call('ZRANGEBYSCORE', KEYS[7], '-inf', stale)) do
+    redis.call('ZREM', KEYS[6], waiter)
+end
+redis.call('ZREMRANGEBYSCORE', KEYS[7], '-inf', stale)
+
+if tonumber(redis.call('GET', KEYS[2]) or '0') <= 0 then return {'sold_out'} end
+local customer = ARGV[1]
+local limit = tonumber(meta[3])
+if limit > 0 and tonumber(redis.call('HGET', KEYS[8], customer) or '0') >= limit then
+    return {'limit_reached'}
+end
+local held = redis.call('HGET', KEYS[5], customer)
+if held then return {'admitted', held, redis.call('ZSCORE', KEYS[3], held)} end
+
+redis.call('ZADD', KEYS[6], 'NX', now, customer)
+redis.call('ZADD', KEYS[7], now, customer)
+local rank = redis.call('ZRANK', KEYS[6], customer)
+local free = tonumber(meta[4]) - redis.call('ZCARD', KEYS[3])
+if rank < free then
+    local expires = now + tonumber(ARGV[4])
+    redis.call('ZADD', KEYS[3], expires, ARGV[3])
+    redis.call('HSET', KEYS[4], ARGV[3], customer)
+    redis.call('HSET', KEYS[5], customer, ARGV[3])
+    redis.call('ZREM', KEYS[6], customer)
+    redis.call('ZREM', KEYS[7], customer)
+    for i = 3, 5 do redis.call('PEXPIREAT', KEYS[i], meta[5]) end
+    return {'admitted', ARGV[3], tostring(expires)}
+end
+redis.call('PEXPIREAT', KEYS[6], meta[5])
+redis.call('PEXPIREAT', KEYS[7], meta[5])
+return {'waiting', tostring(rank - math.max(free, 0) + 1)}
+"#;
+
+/// Spend a purchase token on `quantity` units
+///
+/// KEYS: the sale's keys in `SaleKeys::all` order
+/// ARGV: token, customer, quantity, now (ms)
+const CLAIM_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then return {'closed'} end
+local now = tonumber(ARGV[4])
+local meta = redis.call('HMGET', KEYS[1], 'ends_at', 'per_customer_limit', 'expire_at')
+if now >= tonumber(meta[1]) then return {'ended'} end
+local token, customer, quantity = ARGV[1], ARGV[2], tonumber(ARGV[3])
+local expires = redis.call('ZSCORE', KEYS[3], token)
+if not expires or tonumber(expires) <= now or redis.call('HGET', KEYS[4], token) ~= customer then
+    return {'invalid_token'}
+end
+local limit = tonumber(meta[2])
+local bought = tonumber(redis.call('HGET', KEYS[8], customer) or '0')
+if limit > 0 and bought + quantity > limit then return {'limit_reached', tostring(limit - bought)} end
+local stock = tonumber(redis.call('GET', KEYS[2]) or '0')
+if stock <= 0 then return {'sold_out'} end
+if stock < quantity then return {'insufficient_stock', tostring(stock)} end
+
+redis.call('DECRBY', KEYS[2], quantity)
+redis.call('HINCRBY', KEYS[8], customer, quantity)
+redis.call('PEXPIREAT', KEYS[8], meta[3])
+redis.call('ZREM', KEYS[3], token)
+redis.call('HDEL', KEYS[4], token)
+redis.call('HDEL', KEYS[5], customer)
+return {'claimed'}
+"#;
+
+/// Give back units claimed by a checkout that did not complete
+///
+/// KEYS: meta, stock, bought
+/// ARGV: customer, quantity
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then return 0 end
+redis.call('INCRBY', KEYS[2], ARGV[2])
+if redis.call('HINCRBY', KEYS[3], ARGV[1], -tonumber(ARGV[2])) <= 0 then
+    redis.call('HDEL', KEYS[3], ARGV[1])
+end
+return 1
+"#;
+
+/// Delete the item mapping only if it still points at this sale
+///
+/// KEYS: item
+/// ARGV: sale id
+const UNMAP_ITEM_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) end
+return 0
+"#;
+
+/// Outcome of entering a sale's waiting room
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashSaleEntry {
+    Admitted { token: String, expires_at: DateTime<Utc> },
+    Waiting { position: i64 },
+    NotStarted { starts_at: DateTime<Utc> },
+    SoldOut,
+    LimitReached,
+    Ended,
+    /// The sale is not live
+    Closed,
+}
+
+/// Outcome of spending a purchase token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashSaleClaim {
+    Claimed,
+    /// Unknown, expired or another customer's token
+    InvalidToken,
+    LimitReached { remaining: i64 },
+    SoldOut,
+    InsufficientStock { available: i64 },
+    Ended,
+    Closed,
+}
+
+/// Redis keys of one sale
+struct SaleKeys {
+    meta: String,
+    stock: String,
+    tokens: String,
+    owners: String,
+    holders: String,
+    queue: String,
+    seen: String,
+    bought: String,
+}
+
+impl SaleKeys {
+    fn new(sale_id: Uuid) -> Self {
+        let key = |name: &str| CacheNamespace::FlashSale.key(format!("{{{}}}:{}", sale_id, name));
+        Self {
+            meta: key("meta"),
+            stock: key("stock"),
+            tokens: key("tokens"),
+            owners: key("owners"),
+            holders: key("holders"),
+            queue: key("queue"),
+            seen: key("seen"),
+            bought: key("bought"),
+        }
+    }
+
+    fn all(&self) -> Vec<String> {
+        vec![
+            self.meta.clone(),
+            self.stock.clone(),
+            self.tokens.clone(),
+            self.owners.clone(),
+            self.holders.clone(),
+            self.queue.clone(),
+            self.seen.clone(),
+            self.bought.clone(),
+        ]
+    }
+}
+
+/// Key mapping a product (or one variant) to its live sale
+fn item_key(product_id: Uuid, variant_id: Option<Uuid>) -> String {
+    let variant = variant_id.map(|id| id.to_string()).unwrap_or_else(|| "any".to_string());
+    CacheNamespace::FlashSale.key(format!("item:{}:{}", product_id, variant))
+}
+
+/// The sale a purchase token belongs to
+pub fn token_sale_id(token: &str) -> Option<Uuid> {
+    token.split_once(':').and_then(|(sale_id, _)| sale_id.parse().ok())
+}
+
+fn new_token(sale_id: Uuid) -> String {
+    format!("{}:{}", sale_id, Uuid::new_v4().simple())
+}
+
+fn from_millis(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ms).single().unwrap_or_else(Utc::now)
+}
+
+/// A script reply as strings
+fn reply_parts(value: Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.into_iter().filter_map(value_string).collect(),
+        other => value_string(other).into_iter().collect(),
+    }
+}
+
+fn value_string(value: Value) -> Option<String> {
+    match value {
+        Value::BulkString(data) => Some(String::from_utf8_lossy(&data).to_string()),
+        Value::SimpleString(s) => Some(s),
+        Value::Int(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn value_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(n) => Some(*n),
+        Value::BulkString(data) => std::str::from_utf8(data).ok()?.parse::<f64>().ok().map(|n| n as i64),
+        _ => None,
+    }
+}
+
+fn number(parts: &[String], index: usize) -> CacheResult<i64> {
+    parts
+        .get(index)
+        .and_then(|part| part.parse::<f64>().ok())
+        .map(|n| n as i64)
+        .ok_or_else(|| CacheError::DeserializationError(format!("Unexpected flash sale reply: {:?}", parts)))
+}
+
+impl FlashSaleEntry {
+    fn from_reply(parts: &[String]) -> CacheResult<Self> {
+        Ok(match parts.first().map(String::as_str) {
+            Some("admitted") => Self::Admitted {
+                token: parts.get(1).cloned().unwrap_or_default(),
+                expires_at: from_millis(number(parts, 2)?),
+            },
+            Some("waiting") => Self::Waiting { position: number(parts, 1)? },
+            Some("not_started") => Self::NotStarted { starts_at: from_millis(number(parts, 1)?) },
+            Some("sold_out") => Self::SoldOut,
+            Some("limit_reached") => Self::LimitReached,
+            Some("ended") => Self::Ended,
+            Some("closed") => Self::Closed,
+            _ => {
+                return Err(CacheError::DeserializationError(format!(
+                    "Unexpected flash sale reply: {:?}",
+                    parts
+                )))
+            }
+        })
+    }
+}
+
+impl FlashSaleClaim {
+    fn from_reply(parts: &[String]) -> CacheResult<Self> {
+        Ok(match parts.first().map(String::as_str) {
+            Some("claimed") => Self::Claimed,
+            Some("invalid_token") => Self::InvalidToken,
+            Some("limit_reached") => Self::LimitReached { remaining: number(parts, 1)?.max(0) },
+            Some("sold_out") => Self::SoldOut,
+            Some("insufficient_stock") => Self::InsufficientStock { available: number(parts, 1)? },
+            Some("ended") => Self::Ended,
+            Some("closed") => Self::Closed,
+            _ => {
+                return Err(CacheError::DeserializationError(format!(
+                    "Unexpected flash sale reply: {:?}",
+                    parts
+                )))
+            }
+        })
+    }
+}
+
+/// Redis-backed state of live flash sales
+#[derive(Clone)]
+pub struct FlashSaleStore {
+    pool: RedisPool,
+    token_ttl_ms: i64,
+    queue_timeout_ms: i64,
+}
+
+impl FlashSaleStore {
+    /// Create a new flash sale store
+    pub fn new(pool: RedisPool, config: &FlashSalesConfig) -> Self {
+        Self {
+            pool,
+            token_ttl_ms: (config.token_ttl_secs as i64).saturating_mul(1000),
+            queue_timeout_ms: (config.queue_timeout_secs as i64).saturating_mul(1000),
+        }
+    }
+
+    /// Load a sale's window, limits and stock, replacing anything left from before
+    pub async fn arm(&self, sale: &FlashSale) -> CacheResult<()> {
+        let keys = SaleKeys::new(sale.id);
+        let item = item_key(sale.product_id, sale.variant_id);
+        let expire_at = sale.ends_at.timestamp_millis() + KEY_GRACE_MS;
+        let stock = i64::from(sale.stock_limit - sale.sold_quantity).max(0);
+
+        let mut pipeline = redis::pipe();
+        pipeline.atomic();
+        pipeline.cmd("DEL").arg(keys.all()).ignore();
+        pipeline
+            .cmd("HSET")
+            .arg(&keys.meta)
+            .arg("starts_at").arg(sale.starts_at.timestamp_millis())
+            .arg("ends_at").arg(sale.ends_at.timestamp_millis())
+            .arg("armed_stock").arg(stock)
+            .arg("per_customer_limit").arg(sale.per_customer_limit)
+            .arg("max_active_tokens").arg(sale.max_active_tokens)
+            .arg("expire_at").arg(expire_at)
+            .ignore();
+        pipeline.cmd("SET").arg(&keys.stock).arg(stock).ignore();
+        pipeline.cmd("PEXPIREAT").arg(&keys.meta).arg(expire_at).ignore();
+        pipeline.cmd("PEXPIREAT").arg(&keys.stock).arg(expire_at).ignore();
+
+        let conn = self.pool.get().await?;
+        conn.execute_pipeline(&pipeline).await?;
+
+        let mut mapping = redis::pipe();
+        mapping.cmd("SET").arg(&item).arg(sale.id.to_string()).ignore();
+        mapping.cmd("PEXPIREAT").arg(&item).arg(expire_at).ignore();
+        conn.execute_pipeline(&mapping).await?;
+        Ok(())
+    }
+
+    /// Remove a sale from Redis; returns the units it sold while live, if it was
+    pub async fn disarm(&self, sale: &FlashSale) -> CacheResult<Option<i64>> {
+        let stats = self.stats(sale.id, Utc::now()).await?;
+        let keys = SaleKeys::new(sale.id);
+
+        let conn = self.pool.get().await?;
+        conn.eval(
+            UNMAP_ITEM_SCRIPT,
+            &[item_key(sale.product_id, sale.variant_id)],
+            &[sale.id.to_string()],
+        )
+        .await?;
+        let mut pipeline = redis::pipe();
+        pipeline.cmd("DEL").arg(keys.all()).ignore();
+        conn.execute_pipeline(&pipeline).await?;
+
+        Ok(stats.map(|stats| stats.sold))
+    }
+
+    /// Live counters of a sale; None if it is not live
+    pub async fn stats(&self, sale_id: Uuid, now: DateTime<Utc>) -> CacheResult<Option<FlashSaleStats>> {
+        let keys = SaleKeys::new(sale_id);
+        let mut pipeline = redis::pipe();
+        pipeline.cmd("HGET").arg(&keys.meta).arg("armed_stock");
+        pipeline.cmd("GET").arg(&keys.stock);
+        pipeline.cmd("ZCOUNT").arg(&keys.tokens).arg(now.timestamp_millis() + 1).arg("+inf");
+        pipeline.cmd("ZCOUNT").arg(&keys.seen).arg(now.timestamp_millis() - self.queue_timeout_ms + 1).arg("+inf");
+
+        let conn = self.pool.get().await?;
+        let values = conn.execute_pipeline(&pipeline).await?;
+        let value = |index: usize| values.get(index).and_then(value_i64);
+
+        let Some(armed_stock) = value(0) else {
+            return Ok(None);
+        };
+        let remaining = value(1).unwrap_or_default();
+        Ok(Some(FlashSaleStats {
+            remaining,
+            sold: (armed_stock - remaining).max(0),
+            active_tokens: value(2).unwrap_or_default(),
+            waiting: value(3).unwrap_or_default(),
+        }))
+    }
+
+    /// Admit a customer with a purchase token, or tell them where they are in the queue
+    pub async fn enter(&self, sale_id: Uuid, customer_id: Uuid, now: DateTime<Utc>) -> CacheResult<FlashSaleEntry> {
+        let conn = self.pool.get().await?;
+        let reply = conn
+            .eval(
+                ENTER_SCRIPT,
+                &SaleKeys::new(sale_id).all(),
+                &[
+                    customer_id.to_string(),
+                    now.timestamp_millis().to_string(),
+                    new_token(sale_id),
+                    self.token_ttl_ms.to_string(),
+                    self.queue_timeout_ms.to_string(),
+                ],
+            )
+            .await?;
+        FlashSaleEntry::from_reply(&reply_parts(reply))
+    }
+
+    /// Spend a purchase token on `quantity` units of its sale
+    pub async fn claim(&self, token: &str, customer_id: Uuid, quantity: i64, now: DateTime<Utc>) -> CacheResult<FlashSaleClaim> {
+        let Some(sale_id) = token_sale_id(token) else {
+            return Ok(FlashSaleClaim::InvalidToken);
+        };
+        let conn = self.pool.get().await?;
+        let reply = conn
+            .eval(
+                CLAIM_SCRIPT,
+                &SaleKeys::new(sale_id).all(),
+                &[
+                    token.to_string(),
+                    customer_id.to_string(),
+                    quantity.to_string(),
+                    now.timestamp_millis().to_string(),
+                ],
+            )
+            .await?;
+        FlashSaleClaim::from_reply(&reply_parts(reply))
+    }
+
+    /// Give back units claimed by a checkout that did not complete
+    pub async fn release(&self, sale_id: Uuid, customer_id: Uuid, quantity: i64) -> CacheResult<()> {
+        let keys = SaleKeys::new(sale_id);
+        let conn = self.pool.get().await?;
+        conn.eval(
+            RELEASE_SCRIPT,
+            &[keys.meta, keys.stock, keys.bought],
+            &[customer_id.to_string(), quantity.to_string()],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The live sale covering a product or variant, if any
+    pub async fn sale_for_item(&self, product_id: Uuid, variant_id: Option<Uuid>) -> CacheResult<Option<Uuid>> {
+        let conn = self.pool.get().await?;
+        let mut candidates = vec![item_key(product_id, None)];
+        if variant_id.is_some() {
+            candidates.insert(0, item_key(product_id, variant_id));
+        }
+        for key in candidates {
+            if let Some(data) = conn.get(&key).await? {
+                if let Ok(sale_id) = String::from_utf8_lossy(&data).parse() {
+                    return Ok(Some(sale_id));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_keys_share_hash_tag() {
+        let sale_id = Uuid::new_v4();
+        let keys = SaleKeys::new(sale_id);
+        let tag = format!("{{{}}}", sale_id);
+        assert!(keys.all().iter().all(|key| key.starts_with("flash:sale:") && key.contains(&tag)));
+        assert_eq!(keys.all().len(), 8);
+
+        let product_id = Uuid::new_v4();
+        assert_eq!(item_key(product_id, None), format!("flash:sale:item:{}:any", product_id));
+
+        let token = new_token(sale_id);
+        assert_eq!(token_sale_id(&token), Some(sale_id));
+        assert_eq!(token_sale_id("not-a-token"), None);
+    }
+
+    #[test]
+    fn test_parse_replies() {
+        let expires_at = Utc.timestamp_millis_opt(1_700_000_000_000).unwrap();
+        assert_eq!(
+            FlashSaleEntry::from_reply(&parts(&["admitted", "t", "1700000000000"])).unwrap(),
+            FlashSaleEntry::Admitted { token: "t".to_string(), expires_at }
+        );
+        assert_eq!(
+            FlashSaleEntry::from_reply(&parts(&["waiting", "3"])).unwrap(),
+            FlashSaleEntry::Waiting { position: 3 }
+        );
+        assert_eq!(FlashSaleEntry::from_reply(&parts(&["closed"])).unwrap(), FlashSaleEntry::Closed);
+        assert!(FlashSaleEntry::from_reply(&parts(&["waiting"])).is_err());
+
+        assert_eq!(
+            FlashSaleClaim::from_reply(&parts(&["limit_reached", "-1"])).unwrap(),
+            FlashSaleClaim::LimitReached { remaining: 0 }
+        );
+        assert_eq!(
+            FlashSaleClaim::from_reply(&parts(&["insufficient_stock", "2"])).unwrap(),
+            FlashSaleClaim::InsufficientStock { available: 2 }
+        );
+        assert!(FlashSaleClaim::from_reply(&parts(&["nope"])).is_err());
+    }
+}
@@ -6,6 +6,11 @@
 //! - Message caching
 //! - Pub/Sub for WebSocket broadcasting
 //! - Token blacklisting
+//! - Cache warming on startup
+//! - Flash sale stock counters and waiting rooms
+//! - Storefront cookie sessions
+//! - Per-route response cache policies
+//! - VAT ID validations
 //!
 //! ## Security Features
 //!
@@ -24,20 +29,30 @@
 //! - Cluster support: Horizontal scaling
 //! - TTL support: Automatic key expiration
 
+pub mod auth_session;
 pub mod config;
 pub mod connection;
 pub mod session;
 pub mod rate_limit;
 pub mod pubsub;
 pub mod token;
+pub mod warmup;
+pub mod flash_sale;
+pub mod policy;
+pub mod vat_validation;
 
 // Re-export main types
+pub use auth_session::{AuthSession, AuthSessionStore};
 pub use config::{CacheConfig, RedisConfig, WebSocketSessionConfig};
 pub use connection::{RedisPool, RedisConnection};
 pub use session::{WebSocketSession, SessionStore};
 pub use rate_limit::{RedisRateLimiter, RateLimitInfo};
 pub use pubsub::{RedisPubSub, Subscription};
 pub use token::{TokenBlacklist, BlacklistedToken};
+pub use warmup::{CacheWarmer, WarmupReport, WarmupState, WarmupStep};
+pub use flash_sale::{FlashSaleStore, FlashSaleEntry, FlashSaleClaim};
+pub use policy::{CachePolicy, CachedResponse, ResponseCache};
+pub use vat_validation::VatIdCache;
 
 /// Cache result type alias
 pub type CacheResult<T> = Result<T, CacheError>;
@@ -136,11 +151,14 @@ pub enum CacheNamespace {
     /// API response cache
     ApiResponse,
     
-    /// Session data
+    /// Storefront cookie sessions
     Session,
     
     /// Statistics cache
     Statistics,
+    
+    /// Flash sale counters and waiting rooms
+    FlashSale,
 }
 
 impl CacheNamespace {
@@ -154,6 +172,7 @@ impl CacheNamespace {
             CacheNamespace::ApiResponse => "api:cache",
             CacheNamespace::Session => "session",
             CacheNamespace::Statistics => "stats",
+            CacheNamespace::FlashSale => "flash:sale",
         }
     }
     
@@ -0,0 +1,368 @@
+//! Per-route response cache policies
+//!
+//! Matches request paths against the `[cache.responses]` rules to decide
+//! the `Cache-Control` and `Surrogate-Key` headers of a response, and keeps
+//! `public` responses in memory until they expire or are purged by one of
+//! their surrogate keys or by a URL pattern.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::config::{CachePolicyRule, CacheVisibility, ResponseCacheConfig};
+
+/// The cache policy of one request path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub ttl_secs: u64,
+    pub visibility: CacheVisibility,
+    /// Surrogate keys with the path's segments filled in
+    pub surrogate_keys: Vec<String>,
+}
+
+impl CachePolicy {
+    /// `Cache-Control` header value
+    pub fn cache_control(&self) -> String {
+        match (self.visibility, self.ttl_secs) {
+            (CacheVisibility::NoStore, _) => "no-store".to_string(),
+            (CacheVisibility::Private, 0) => "private, no-cache".to_string(),
+            (CacheVisibility::Private, ttl) => format!("private, max-age={}", ttl),
+            (CacheVisibility::Public, 0) => "public, no-cache".to_string(),
+            (CacheVisibility::Public, ttl) => format!("public, max-age={}", ttl),
+        }
+    }
+
+    /// `Surrogate-Key` header value; None without surrogate keys
+    pub fn surrogate_key_header(&self) -> Option<String> {
+        (!self.surrogate_keys.is_empty()).then(|| self.surrogate_keys.join(" "))
+    }
+
+    /// Whether responses are kept in the shared response cache
+    pub fn is_shared(&self) -> bool {
+        self.visibility == CacheVisibility::Public && self.ttl_secs > 0
+    }
+}
+
+/// A segment of a rule's path
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+/// A rule with its path split into segments
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    segments: Vec<Segment>,
+    /// The path ended in `*`, matching any further segments
+    prefix: bool,
+    rule: CachePolicyRule,
+}
+
+impl CompiledRule {
+    fn new(rule: CachePolicyRule) -> Self {
+        let (path, prefix) = match rule.path.strip_suffix('*') {
+            Some(path) => (path.trim_end_matches('/'), true),
+            None => (rule.path.as_str(), false),
+        };
+        let segments = split_path(path)
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => Segment::Param(name.to_string()),
+                None => Segment::Literal(segment.to_string()),
+            })
+            .collect();
+        Self { segments, prefix, rule }
+    }
+
+    /// The `:name` segments of a matching path
+    fn matches<'a>(&self, path: &'a str) -> Option<HashMap<&str, &'a str>> {
+        let parts: Vec<&str> = split_path(path).collect();
+        if parts.len() < self.segments.len() || (!self.prefix && parts.len() != self.segments.len()) {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        for (segment, part) in self.segments.iter().zip(&parts) {
+            match segment {
+                Segment::Literal(literal) if literal == part => {}
+                Segment::Literal(_) => return None,
+                Segment::Param(name) => {
+                    params.insert(name.as_str(), *part);
+                }
+            }
+        }
+        Some(params)
+    }
+
+    fn policy(&self, params: &HashMap<&str, &str>) -> CachePolicy {
+        let surrogate_keys = self
+            .rule
+            .surrogate_keys
+            .iter()
+            .map(|key| {
+                params
+                    .iter()
+                    .fold(key.clone(), |key, (name, value)| key.replace(&format!("{{{}}}", name), value))
+            })
+            .collect();
+        CachePolicy {
+            ttl_secs: self.rule.ttl_secs,
+            visibility: self.rule.visibility,
+            surrogate_keys,
+        }
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// A response kept in the response cache
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+    pub surrogate_keys: Vec<String>,
+    /// Request path, matched by URL pattern purges
+    pub path: String,
+    pub stored_at: Instant,
+    pub expires_at: Instant,
+}
+
+impl CachedResponse {
+    /// Seconds since the response was stored (`Age` header)
+    pub fn age_secs(&self) -> u64 {
+        self.stored_at.elapsed().as_secs()
+    }
+}
+
+/// Response cache: the configured policies and the responses kept in memory
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    rules: Vec<CompiledRule>,
+    entries: DashMap<String, CachedResponse>,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        let rules = config.rules.iter().cloned().map(CompiledRule::new).collect();
+        Self {
+            config,
+            rules,
+            entries: DashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Request header staff send to skip cached responses
+    pub fn bypass_header(&self) -> &str {
+        &self.config.bypass_header
+    }
+
+    /// The policy of the first rule matching a path
+    pub fn policy_for(&self, path: &str) -> Option<CachePolicy> {
+        if !self.config.enabled {
+            return None;
+        }
+        self.rules
+            .iter()
+            .find_map(|rule| rule.matches(path).map(|params| rule.policy(&params)))
+    }
+
+    /// Cache key of a request
+    pub fn key(method: &str, path_and_query: &str) -> String {
+        format!("{} {}", method, path_and_query)
+    }
+
+    /// A cached response that hasn't expired
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let entry = self.entries.get(key)?;
+        if entry.expires_at > Instant::now() {
+            return Some(entry.clone());
+        }
+        drop(entry);
+        self.entries.remove(key);
+        None
+    }
+
+    /// Keep a response for the policy's TTL; dropped when the cache is full
+    /// even after removing expired responses
+    pub fn insert(
+        &self,
+        key: String,
+        path: &str,
+        policy: &CachePolicy,
+        status: u16,
+        content_type: Option<String>,
+        body: Vec<u8>,
+    ) {
+        if !policy.is_shared() {
+            return;
+        }
+        let now = Instant::now();
+        if self.entries.len() >= self.config.max_entries && !self.entries.contains_key(&key) {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+            if self.entries.len() >= self.config.max_entries {
+                tracing::debug!("Response cache full, not caching {}", key);
+                return;
+            }
+        }
+
+        self.entries.insert(
+            key,
+            CachedResponse {
+                status,
+                content_type,
+                body,
+                surrogate_keys: policy.surrogate_keys.clone(),
+                path: path.to_string(),
+                stored_at: now,
+                expires_at: now + Duration::from_secs(policy.ttl_secs),
+            },
+        );
+    }
+
+    /// Remove responses tagged with a surrogate key; returns how many
+    pub fn purge_surrogate_key(&self, surrogate_key: &str) -> usize {
+        self.purge(|entry| entry.surrogate_keys.iter().any(|key| key == surrogate_key))
+    }
+
+    /// Remove responses whose path matches a pattern, where `*` matches
+    /// any characters; returns how many
+    pub fn purge_url_pattern(&self, pattern: &str) -> usize {
+        self.purge(|entry| glob_match(pattern, &entry.path))
+    }
+
+    /// Remove every response; returns how many
+    pub fn purge_all(&self) -> usize {
+        self.purge(|_| true)
+    }
+
+    /// Responses currently kept
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn purge(&self, matches: impl Fn(&CachedResponse) -> bool) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| !matches(entry));
+        before.saturating_sub(self.entries.len())
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(path: &str, ttl_secs: u64, visibility: CacheVisibility, keys: &[&str]) -> CachePolicyRule {
+        CachePolicyRule {
+            path: path.to_string(),
+            ttl_secs,
+            visibility,
+            surrogate_keys: keys.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    fn cache(rules: Vec<CachePolicyRule>) -> ResponseCache {
+        ResponseCache::new(ResponseCacheConfig {
+            enabled: true,
+            rules,
+            ..ResponseCacheConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_policy_for() {
+        let cache = cache(vec![
+            rule("/api/v1/products/:id", 300, CacheVisibility::Public, &["products", "product:{id}"]),
+            rule("/api/v1/customers/*", 60, CacheVisibility::Private, &[]),
+            rule("/api/v1/checkout/*", 0, CacheVisibility::NoStore, &[]),
+        ]);
+
+        let product = cache.policy_for("/api/v1/products/abc").unwrap();
+        assert_eq!(product.surrogate_keys, vec!["products", "product:abc"]);
+        assert_eq!(product.cache_control(), "public, max-age=300");
+        assert_eq!(product.surrogate_key_header().as_deref(), Some("products product:abc"));
+        assert!(product.is_shared());
+
+        // `:id` matches one segment only
+        assert!(cache.policy_for("/api/v1/products/abc/variants").is_none());
+
+        let customer = cache.policy_for("/api/v1/customers/me/invoices").unwrap();
+        assert_eq!(customer.cache_control(), "private, max-age=60");
+        assert!(!customer.is_shared());
+        assert!(cache.policy_for("/api/v1/customers").is_some());
+
+        assert_eq!(cache.policy_for("/api/v1/checkout/initiate").unwrap().cache_control(), "no-store");
+        assert!(cache.policy_for("/api/v1/orders").is_none());
+    }
+
+    #[test]
+    fn test_disabled_cache_has_no_policies() {
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            rules: vec![rule("/api/v1/*", 60, CacheVisibility::Public, &[])],
+            ..ResponseCacheConfig::default()
+        });
+        assert!(cache.policy_for("/api/v1/products").is_none());
+    }
+
+    #[test]
+    fn test_purge() {
+        let cache = cache(vec![rule("/api/v1/products/:id", 300, CacheVisibility::Public, &["product:{id}"])]);
+        for id in ["a", "b"] {
+            let path = format!("/api/v1/products/{}", id);
+            let policy = cache.policy_for(&path).unwrap();
+            cache.insert(ResponseCache::key("GET", &path), &path, &policy, 200, None, b"{}".to_vec());
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("GET /api/v1/products/a").is_some());
+
+        assert_eq!(cache.purge_surrogate_key("product:a"), 1);
+        assert!(cache.get("GET /api/v1/products/a").is_none());
+        assert_eq!(cache.purge_url_pattern("/api/v1/orders/*"), 0);
+        assert_eq!(cache.purge_url_pattern("/api/v1/products/*"), 1);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("/api/v1/products/*", "/api/v1/products/abc"));
+        assert!(glob_match("*/variants", "/api/v1/products/abc/variants"));
+        assert!(glob_match("/api/*/products/*", "/api/v1/products/abc"));
+        assert!(glob_match("/api/v1/products", "/api/v1/products"));
+        assert!(!glob_match("/api/v1/products", "/api/v1/products/abc"));
+        assert!(!glob_match("/api/v1/orders/*", "/api/v1/products/abc"));
+        assert!(!glob_match("/a*bc", "/abc/d"));
+    }
+}
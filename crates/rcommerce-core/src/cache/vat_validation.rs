@@ -0,0 +1,87 @@
+//! In-memory cache of VAT ID validations
+//!
+//! Sits in front of the `vat_id_validations` table so repeat checkouts with
+//! the same VAT ID don't query Postgres or VIES. Provisional results (VIES
+//! was unavailable) are kept for a shorter time, so the next checkout tries
+//! VIES again without waiting on it for every order in between.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::tax::VatValidationResult;
+
+/// VAT validation results by full VAT ID, with their expiry
+pub struct VatIdCache {
+    entries: DashMap<String, (VatValidationResult, Instant)>,
+    ttl: Duration,
+    provisional_ttl: Duration,
+}
+
+impl VatIdCache {
+    /// Keep VIES answers for `ttl`, provisional results for `provisional_ttl`
+    /// (at most `ttl`)
+    pub fn new(ttl: Duration, provisional_ttl: Duration) -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl,
+            provisional_ttl: provisional_ttl.min(ttl),
+        }
+    }
+
+    /// A result that hasn't expired
+    pub fn get(&self, vat_id: &str) -> Option<VatValidationResult> {
+        let entry = self.entries.get(vat_id)?;
+        if entry.1 > Instant::now() {
+            return Some(entry.0.clone());
+        }
+        drop(entry);
+        self.entries.remove(vat_id);
+        None
+    }
+
+    pub fn insert(&self, vat_id: &str, result: &VatValidationResult) {
+        let ttl = if result.provisional { self.provisional_ttl } else { self.ttl };
+        if ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .insert(vat_id.to_string(), (result.clone(), Instant::now() + ttl));
+    }
+
+    pub fn remove(&self, vat_id: &str) {
+        self.entries.remove(vat_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn result(provisional: bool) -> VatValidationResult {
+        VatValidationResult {
+            is_valid: true,
+            country_code: "DE".to_string(),
+            vat_number: "123456789".to_string(),
+            business_name: None,
+            business_address: None,
+            validated_at: Utc::now(),
+            error_message: None,
+            provisional,
+        }
+    }
+
+    #[test]
+    fn test_provisional_results_expire_sooner() {
+        let cache = VatIdCache::new(Duration::from_secs(3600), Duration::ZERO);
+        cache.insert("DE123456789", &result(true));
+        assert!(cache.get("DE123456789").is_none());
+
+        cache.insert("DE123456789", &result(false));
+        assert!(!cache.get("DE123456789").unwrap().provisional);
+
+        cache.remove("DE123456789");
+        assert!(cache.get("DE123456789").is_none());
+    }
+}
@@ -0,0 +1,254 @@
+//! Cache warming for zero-downtime deploys
+//!
+//! Runs the hot read paths (top products, categories, collections) once
+//! before the server reports ready, so the first requests after a deploy
+//! don't hit cold Postgres buffers or empty Redis keys. Also checks that the
+//! planner statistics backing product search are fresh, re-analyzing stale
+//! tables.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::sync::RwLock;
+
+use crate::cache::{KeyPrefix, RedisPool};
+use crate::config::CacheWarmupConfig;
+
+/// Tables whose statistics product search depends on
+const SEARCH_TABLES: &[&str] = &["products", "product_variants", "product_categories"];
+
+/// Outcome of one warmup step
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupStep {
+    pub name: String,
+    pub items: usize,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a complete warmup run
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub steps: Vec<WarmupStep>,
+}
+
+impl WarmupReport {
+    /// Whether every step completed without error
+    pub fn is_complete(&self) -> bool {
+        self.steps.iter().all(|s| s.error.is_none())
+    }
+}
+
+/// Shared readiness flag, flipped once the first warmup finishes
+#[derive(Clone, Default)]
+pub struct WarmupState {
+    ready: Arc<AtomicBool>,
+    last_report: Arc<RwLock<Option<WarmupReport>>>,
+}
+
+impl WarmupState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the server has finished warming and can take traffic
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Mark the server ready without a report (warmup disabled or timed out)
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Record a finished warmup and mark the server ready
+    pub async fn record(&self, report: WarmupReport) {
+        *self.last_report.write().await = Some(report);
+        self.mark_ready();
+    }
+
+    /// Report from the most recent warmup, if any
+    pub async fn last_report(&self) -> Option<WarmupReport> {
+        self.last_report.read().await.clone()
+    }
+}
+
+/// Pre-populates hot caches
+#[derive(Clone)]
+pub struct CacheWarmer {
+    pool: PgPool,
+    redis: Option<RedisPool>,
+    config: CacheWarmupConfig,
+    prefix: KeyPrefix,
+}
+
+impl CacheWarmer {
+    pub fn new(pool: PgPool, redis: Option<RedisPool>, config: CacheWarmupConfig) -> Self {
+        Self {
+            pool,
+            redis,
+            config,
+            prefix: KeyPrefix::new("rcommerce:warm"),
+        }
+    }
+
+    pub fn config(&self) -> &CacheWarmupConfig {
+        &self.config
+    }
+
+    /// Run every warmup step. Failures are recorded per step and never abort
+    /// the run, since a partially warm cache is still better than none.
+    pub async fn warm(&self) -> WarmupReport {
+        let started_at = Utc::now();
+        tracing::info!("Warming caches...");
+
+        let top_products = format!(
+            r#"SELECT p.id, p.title, p.slug, p.price, p.currency, p.inventory_quantity
+               FROM products p
+               LEFT JOIN order_items oi ON oi.product_id = p.id
+                   AND oi.created_at > NOW() - INTERVAL '30 days'
+               WHERE p.is_active = true
+               GROUP BY p.id
+               ORDER BY COALESCE(SUM(oi.quantity), 0) DESC, p.created_at DESC
+               LIMIT {}"#,
+            self.config.top_products.max(0)
+        );
+
+        let steps = vec![
+            self.warm_query("top_products", &top_products).await,
+            self.warm_query(
+                "categories",
+                "SELECT id, name, slug, parent_id, sort_order FROM product_categories \
+                 WHERE is_active = true ORDER BY sort_order, name",
+            )
+            .await,
+            self.warm_query(
+                "collections",
+                "SELECT id, title, handle FROM collections \
+                 WHERE published_at IS NOT NULL AND published_at <= NOW() ORDER BY title",
+            )
+            .await,
+            self.check_search_index().await,
+        ];
+
+        let report = WarmupReport {
+            started_at,
+            finished_at: Utc::now(),
+            steps,
+        };
+
+        for step in &report.steps {
+            match step.error {
+                Some(ref e) => tracing::warn!("Cache warmup step '{}' failed: {}", step.name, e),
+                None => tracing::info!(
+                    "Cache warmup step '{}': {} items in {}ms",
+                    step.name, step.items, step.duration_ms
+                ),
+            }
+        }
+
+        report
+    }
+
+    /// Run a query, and store the rows as JSON in Redis when available
+    async fn warm_query(&self, name: &str, sql: &str) -> WarmupStep {
+        let start = Instant::now();
+        let wrapped = format!("SELECT COALESCE(json_agg(t), '[]'::json) FROM ({}) t", sql);
+
+        let result: Result<usize, String> = async {
+            let rows: serde_json::Value = sqlx::query_scalar(&wrapped)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            let items = rows.as_array().map(|a| a.len()).unwrap_or(0);
+
+            if let Some(ref redis) = self.redis {
+                let conn = redis.get().await.map_err(|e| e.to_string())?;
+                let bytes = serde_json::to_vec(&rows).map_err(|e| e.to_string())?;
+                conn.setex(&self.prefix.key(name), self.config.ttl_secs, &bytes)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+
+            Ok(items)
+        }
+        .await;
+
+        step(name, start, result)
+    }
+
+    /// Re-analyze search tables whose planner statistics are stale
+    async fn check_search_index(&self) -> WarmupStep {
+        let start = Instant::now();
+
+        let result: Result<usize, String> = async {
+            let rows: Vec<(String, Option<DateTime<Utc>>)> = sqlx::query_as(
+                "SELECT relname::text, GREATEST(last_analyze, last_autoanalyze) \
+                 FROM pg_stat_user_tables WHERE relname = ANY($1)",
+            )
+            .bind(SEARCH_TABLES)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+            let cutoff = Utc::now() - chrono::Duration::hours(self.config.search_max_age_hours);
+            let mut reanalyzed = 0;
+            for (table, last_analyzed) in rows {
+                if last_analyzed.map(|t| t < cutoff).unwrap_or(true) {
+                    tracing::info!("Search statistics for '{}' are stale, running ANALYZE", table);
+                    // Table names come from SEARCH_TABLES, not user input
+                    sqlx::query(&format!("ANALYZE {}", table))
+                        .execute(&self.pool)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    reanalyzed += 1;
+                }
+            }
+
+            Ok(reanalyzed)
+        }
+        .await;
+
+        step("search_index", start, result)
+    }
+}
+
+fn step(name: &str, start: Instant, result: Result<usize, String>) -> WarmupStep {
+    let duration_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(items) => WarmupStep { name: name.to_string(), items, duration_ms, error: None },
+        Err(e) => WarmupStep { name: name.to_string(), items: 0, duration_ms, error: Some(e) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_warmup_state_flips_ready() {
+        let state = WarmupState::new();
+        assert!(!state.is_ready());
+        assert!(state.last_report().await.is_none());
+
+        let now = Utc::now();
+        state
+            .record(WarmupReport {
+                started_at: now,
+                finished_at: now,
+                steps: vec![step("categories", Instant::now(), Err("boom".to_string()))],
+            })
+            .await;
+
+        assert!(state.is_ready());
+        let report = state.last_report().await.unwrap();
+        assert!(!report.is_complete());
+    }
+}
@@ -11,7 +11,7 @@ pub type CountryCode = String;
 pub type LanguageCode = String;
 
 /// Address structure used throughout the system
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
 pub struct Address {
     pub id: Uuid,
     pub customer_id: Uuid,
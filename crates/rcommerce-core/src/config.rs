@@ -48,6 +48,117 @@ pub struct Config {
     
     #[serde(default)]
     pub tax: TaxConfig,
+    
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    
+    #[serde(default)]
+    pub audit: AuditConfig,
+    
+    #[serde(default)]
+    pub soft_delete: SoftDeleteConfig,
+    
+    #[serde(default)]
+    pub formatting: FormattingConfig,
+    
+    #[serde(default)]
+    pub geoip: GeoIpConfig,
+    
+    #[serde(default)]
+    pub order_archive: OrderArchiveConfig,
+    
+    #[serde(default)]
+    pub webhook_replay: WebhookReplayConfig,
+    
+    #[serde(default)]
+    pub subscription_billing: SubscriptionBillingConfig,
+    
+    #[serde(default)]
+    pub inventory: crate::inventory::InventoryConfig,
+    
+    #[serde(default)]
+    pub order_numbers: crate::order::OrderNumberConfig,
+    
+    #[serde(default)]
+    pub stock_adjustments: StockAdjustmentConfig,
+    
+    #[serde(default)]
+    pub fx: FxConfig,
+    
+    #[serde(default)]
+    pub delivery: DeliveryConfig,
+    
+    #[serde(default)]
+    pub returns: ReturnsConfig,
+    
+    #[serde(default)]
+    pub checkout_fields: CheckoutFieldsConfig,
+    
+    #[serde(default)]
+    pub gift_cards: GiftCardsConfig,
+    
+    #[serde(default)]
+    pub flash_sales: FlashSalesConfig,
+    
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    
+    #[serde(default)]
+    pub sessions: SessionConfig,
+    
+    #[serde(default)]
+    pub scheduler: JobSchedulerConfig,
+    
+    #[serde(default)]
+    pub marketplaces: MarketplaceConfig,
+    
+    #[serde(default)]
+    pub automation: AutomationConfig,
+    
+    #[serde(default)]
+    pub purchasing: PurchasingConfig,
+    
+    #[serde(default)]
+    pub supplier_feeds: SupplierFeedsConfig,
+    
+    #[serde(default)]
+    pub fulfillment: FulfillmentConfig,
+    
+    #[serde(default)]
+    pub landed_cost: LandedCostConfig,
+    
+    #[serde(default)]
+    pub fraud: FraudConfig,
+    
+    #[serde(default)]
+    pub printing: PrintingConfig,
+    
+    #[serde(default)]
+    pub reports: ReportsConfig,
+    
+    #[serde(default)]
+    pub redirects: RedirectsConfig,
+    
+    #[serde(default)]
+    pub observability: ObservabilityConfig,
+    
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    
+    #[serde(default)]
+    pub admin_ui: AdminUiConfig,
+    
+    #[serde(default)]
+    pub websocket: crate::websocket::WebSocketConfig,
+    
+    #[serde(default)]
+    pub events: crate::events::EventsConfig,
 }
 
 impl Config {
@@ -98,17 +209,437 @@ impl Config {
         if self.server.port == 0 {
             return Err(Error::Config("Invalid server port".to_string()));
         }
+        let invalid_proxy = self
+            .server
+            .trusted_proxies
+            .iter()
+            .find(|proxy| !crate::repository::is_valid_ip_network(proxy));
+        if let Some(proxy) = invalid_proxy {
+            return Err(Error::Config(format!(
+                "server.trusted_proxies: '{}' is not an address or CIDR network",
+                proxy
+            )));
+        }
         
         // Validate database config
         if self.database.pool_size == 0 {
             return Err(Error::Config("Database pool size must be > 0".to_string()));
         }
+        for replica in &self.database.read_replicas {
+            if replica.host.trim().is_empty() {
+                return Err(Error::Config("database.read_replicas entries need a host".to_string()));
+            }
+            if replica.pool_size == Some(0) {
+                return Err(Error::Config(format!("Read replica {} pool size must be > 0", replica.host)));
+            }
+        }
+        if !self.database.read_replicas.is_empty() && self.database.replica_retry_secs == 0 {
+            return Err(Error::Config("database.replica_retry_secs must be > 0".to_string()));
+        }
         
         // Validate cache config
         if self.cache.max_size_mb > Some(10000) {
             return Err(Error::Config("Cache size too large (max 10GB)".to_string()));
         }
         
+        // Validate traffic capture config
+        if !(0.0..=1.0).contains(&self.capture.sample_rate) {
+            return Err(Error::Config("capture.sample_rate must be between 0.0 and 1.0".to_string()));
+        }
+        
+        // Validate access log config
+        self.access_log.validate()?;
+        
+        // Validate audit log config
+        self.audit.validate()?;
+        
+        // Validate soft delete config
+        if self.soft_delete.purge_batch_size <= 0 {
+            return Err(Error::Config("soft_delete.purge_batch_size must be positive".to_string()));
+        }
+        
+        // Validate price formatting config
+        if !crate::services::FormattingService::new(&self.formatting).has_locale(&self.formatting.default_locale) {
+            return Err(Error::Config(format!(
+                "formatting.default_locale '{}' is not a known locale; add it under [formatting.locales]",
+                self.formatting.default_locale
+            )));
+        }
+        
+        // Validate GeoIP config
+        if self.geoip.enabled && self.geoip.provider == GeoIpProvider::Api && self.geoip.api_url.is_none() {
+            return Err(Error::Config("geoip.api_url is required when geoip.provider = \"api\"".to_string()));
+        }
+        if self.geoip.default_currency.parse::<crate::models::Currency>().is_err() {
+            return Err(Error::Config(format!("geoip.default_currency '{}' is not a supported currency", self.geoip.default_currency)));
+        }
+        for (country, currency) in &self.geoip.country_currencies {
+            if currency.parse::<crate::models::Currency>().is_err() {
+                return Err(Error::Config(format!("geoip.country_currencies.{} '{}' is not a supported currency", country, currency)));
+            }
+        }
+        
+        // Validate order archival config
+        for status in &self.order_archive.statuses {
+            if !ARCHIVABLE_ORDER_STATUSES.contains(&status.as_str()) {
+                return Err(Error::Config(format!(
+                    "order_archive.statuses '{}' is not archivable; use one of: {}",
+                    status,
+                    ARCHIVABLE_ORDER_STATUSES.join(", ")
+                )));
+            }
+        }
+        if self.order_archive.archive_after_days == 0 || self.order_archive.batch_size == 0 {
+            return Err(Error::Config("order_archive.archive_after_days and order_archive.batch_size must be positive".to_string()));
+        }
+        
+        // Validate webhook replay config
+        let replay = &self.webhook_replay;
+        if replay.default_rate_per_second == 0 || replay.default_rate_per_second > replay.max_rate_per_second {
+            return Err(Error::Config(
+                "webhook_replay.default_rate_per_second must be between 1 and webhook_replay.max_rate_per_second".to_string()
+            ));
+        }
+        if replay.max_range_days == 0 || replay.batch_size == 0 {
+            return Err(Error::Config("webhook_replay.max_range_days and webhook_replay.batch_size must be positive".to_string()));
+        }
+        
+        // Validate subscription billing config
+        if self.subscription_billing.interval_secs < 60 {
+            return Err(Error::Config("subscription_billing.interval_secs must be at least 60".to_string()));
+        }
+        if self.dunning.max_retries < 1 || self.dunning.retry_intervals_days.iter().any(|d| *d < 0) {
+            return Err(Error::Config(
+                "dunning.max_retries must be positive and dunning.retry_intervals_days must not be negative".to_string()
+            ));
+        }
+        
+        // Validate stock adjustment approval config
+        let approver_roles = &self.stock_adjustments.approver_roles;
+        if approver_roles.is_empty() || approver_roles.contains(&crate::models::CustomerRole::Customer) {
+            return Err(Error::Config(
+                "stock_adjustments.approver_roles must list staff roles (manager, admin)".to_string()
+            ));
+        }
+        
+        // Validate inventory config
+        if self.inventory.reservation_timeout_minutes == 0 {
+            return Err(Error::Config("inventory.reservation_timeout_minutes must be positive".to_string()));
+        }
+        
+        self.order_numbers.validate()?;
+        
+        // Validate purchasing config
+        if self.purchasing.reorder_interval_secs < 60 {
+            return Err(Error::Config("purchasing.reorder_interval_secs must be at least 60".to_string()));
+        }
+        if self.fulfillment.backorder_interval_secs < 60 {
+            return Err(Error::Config("fulfillment.backorder_interval_secs must be at least 60".to_string()));
+        }
+        if self.purchasing.alert_roles.contains(&crate::models::CustomerRole::Customer) {
+            return Err(Error::Config(
+                "purchasing.alert_roles must list staff roles (manager, admin)".to_string()
+            ));
+        }
+        if self.purchasing.dispatch_interval_secs < 60 {
+            return Err(Error::Config("purchasing.dispatch_interval_secs must be at least 60".to_string()));
+        }
+        if self.purchasing.max_dispatch_attempts < 1 || self.purchasing.ack_overdue_hours < 0 {
+            return Err(Error::Config(
+                "purchasing.max_dispatch_attempts must be positive and ack_overdue_hours not negative".to_string()
+            ));
+        }
+        if self.purchasing.price_tolerance_percent.is_sign_negative() {
+            return Err(Error::Config("purchasing.price_tolerance_percent must not be negative".to_string()));
+        }
+        if !self.purchasing.public_url.starts_with("https://") && !self.purchasing.public_url.starts_with("http://") {
+            return Err(Error::Config("purchasing.public_url must be an http(s) URL".to_string()));
+        }
+        if self.purchasing.sftp_command.trim().is_empty() || self.purchasing.sftp_timeout_secs == 0 {
+            return Err(Error::Config(
+                "purchasing.sftp_command must be set and sftp_timeout_secs positive".to_string()
+            ));
+        }
+        
+        // Validate FX config
+        if self.fx.base_currency.parse::<crate::models::Currency>().is_err() {
+            return Err(Error::Config(format!("fx.base_currency '{}' is not a supported currency", self.fx.base_currency)));
+        }
+        
+        // Validate delivery scheduling config
+        crate::shipping::DeliveryScheduler::from_config(&self.delivery)?;
+        
+        // Validate per-location carrier accounts
+        self.shipping.validate()?;
+        
+        // Validate landed cost config
+        self.landed_cost.validate()?;
+        
+        // Validate fraud screening config
+        self.fraud.validate()?;
+        
+        // Validate printer profiles
+        self.printing.validate()?;
+        
+        // Validate supplier feeds
+        self.supplier_feeds.validate()?;
+        
+        // Validate returns config
+        if self.returns.label_weight <= rust_decimal::Decimal::ZERO {
+            return Err(Error::Config("returns.label_weight must be positive".to_string()));
+        }
+        
+        // Validate custom checkout field definitions
+        crate::services::checkout_field_service::CheckoutFieldSchema::from_config(&self.checkout_fields)?;
+        
+        // Validate gift card config
+        let gift_cards = &self.gift_cards;
+        if gift_cards.currency.parse::<crate::models::Currency>().is_err() {
+            return Err(Error::Config(format!("gift_cards.currency '{}' is not a supported currency", gift_cards.currency)));
+        }
+        if gift_cards.denominations.iter().any(|amount| *amount <= rust_decimal::Decimal::ZERO) {
+            return Err(Error::Config("gift_cards.denominations must be positive".to_string()));
+        }
+        if gift_cards.allow_custom_amount
+            && (gift_cards.min_custom_amount <= rust_decimal::Decimal::ZERO || gift_cards.min_custom_amount > gift_cards.max_custom_amount)
+        {
+            return Err(Error::Config(
+                "gift_cards.min_custom_amount must be positive and no more than gift_cards.max_custom_amount".to_string()
+            ));
+        }
+        if gift_cards.expiry_interval_secs < 60 {
+            return Err(Error::Config("gift_cards.expiry_interval_secs must be at least 60".to_string()));
+        }
+        
+        // Validate flash sale config
+        let flash_sales = &self.flash_sales;
+        if flash_sales.token_ttl_secs == 0 || flash_sales.queue_timeout_secs == 0 || flash_sales.retry_after_secs == 0 {
+            return Err(Error::Config(
+                "flash_sales.token_ttl_secs, queue_timeout_secs and retry_after_secs must be positive".to_string()
+            ));
+        }
+        if flash_sales.queue_timeout_secs <= flash_sales.retry_after_secs {
+            return Err(Error::Config(
+                "flash_sales.queue_timeout_secs must be longer than flash_sales.retry_after_secs".to_string()
+            ));
+        }
+        if flash_sales.default_max_active_tokens == 0 {
+            return Err(Error::Config("flash_sales.default_max_active_tokens must be positive".to_string()));
+        }
+        
+        // Validate idempotency config
+        let idempotency = &self.idempotency;
+        if idempotency.key_ttl_hours == 0 || idempotency.lock_timeout_secs == 0 || idempotency.max_body_bytes == 0 {
+            return Err(Error::Config(
+                "idempotency.key_ttl_hours, lock_timeout_secs and max_body_bytes must be positive".to_string()
+            ));
+        }
+        if idempotency.purge_interval_secs < 60 {
+            return Err(Error::Config("idempotency.purge_interval_secs must be at least 60".to_string()));
+        }
+        
+        // Validate secrets config
+        if self.secrets.provider == SecretsProvider::File && self.secrets.master_key_file.is_none() {
+            return Err(Error::Config("secrets.master_key_file is required with provider = \"file\"".to_string()));
+        }
+        
+        // Validate cookie sessions
+        if self.sessions.enabled {
+            if !matches!(self.cache.cache_type, CacheType::Redis) || self.cache.redis_url.is_none() {
+                return Err(Error::Config("sessions.enabled requires the Redis cache (cache.cache_type = \"redis\")".to_string()));
+            }
+            if self.sessions.same_site == SameSite::None && !self.sessions.secure {
+                return Err(Error::Config("sessions.same_site = \"none\" requires sessions.secure".to_string()));
+            }
+            if self.server.cors.allowed_origins.iter().any(|origin| origin == "*") {
+                return Err(Error::Config("Cookie sessions need explicit server.cors.allowed_origins, not \"*\"".to_string()));
+            }
+        }
+        
+        // Validate job scheduler
+        if self.scheduler.poll_interval_secs == 0 {
+            return Err(Error::Config("scheduler.poll_interval_secs must be at least 1".to_string()));
+        }
+        if self.scheduler.lease_secs < 60 {
+            return Err(Error::Config("scheduler.lease_secs must be at least 60".to_string()));
+        }
+        if self.scheduler.concurrency == 0 {
+            return Err(Error::Config("scheduler.concurrency must be at least 1".to_string()));
+        }
+        if self.scheduler.lock_ttl_secs < 15 {
+            return Err(Error::Config("scheduler.lock_ttl_secs must be at least 15".to_string()));
+        }
+        self.scheduler.retry.validate("scheduler.retry")?;
+        for (job, policy) in &self.scheduler.retry_policies {
+            policy.validate(&format!("scheduler.retry_policies.{}", job))?;
+        }
+        
+        // Validate marketplace sync
+        if self.marketplaces.sync_interval_secs < 60 {
+            return Err(Error::Config("marketplaces.sync_interval_secs must be at least 60".to_string()));
+        }
+        if self.marketplaces.batch_size == 0 {
+            return Err(Error::Config("marketplaces.batch_size must be at least 1".to_string()));
+        }
+        if let Some(ref amazon) = self.marketplaces.amazon {
+            if amazon.seller_id.is_empty() || amazon.marketplace_id.is_empty() || amazon.refresh_token.is_empty() {
+                return Err(Error::Config("marketplaces.amazon needs seller_id, marketplace_id and refresh_token".to_string()));
+            }
+        }
+        if let Some(ref ebay) = self.marketplaces.ebay {
+            if ebay.client_id.is_empty() || ebay.refresh_token.is_empty() {
+                return Err(Error::Config("marketplaces.ebay needs client_id and refresh_token".to_string()));
+            }
+        }
+        
+        // Validate automation rules
+        if self.automation.run_interval_secs < 60 {
+            return Err(Error::Config("automation.run_interval_secs must be at least 60".to_string()));
+        }
+        if self.automation.batch_size == 0 {
+            return Err(Error::Config("automation.batch_size must be at least 1".to_string()));
+        }
+        if let Some(ref url) = self.automation.slack_webhook_url {
+            if !url.starts_with("https://") {
+                return Err(Error::Config("automation.slack_webhook_url must be an https URL".to_string()));
+            }
+        }
+        
+        // Validate scheduled reports
+        if self.reports.run_interval_secs < 60 {
+            return Err(Error::Config("reports.run_interval_secs must be at least 60".to_string()));
+        }
+        if self.reports.retention_days == 0 {
+            return Err(Error::Config("reports.retention_days must be at least 1".to_string()));
+        }
+        if self.reports.max_rows == 0 || self.reports.batch_size == 0 {
+            return Err(Error::Config("reports.max_rows and reports.batch_size must be at least 1".to_string()));
+        }
+        if !self.reports.public_url.starts_with("https://") && !self.reports.public_url.starts_with("http://") {
+            return Err(Error::Config("reports.public_url must be an http(s) URL".to_string()));
+        }
+        if self.reports.sales_refresh_interval_secs < 60 {
+            return Err(Error::Config("reports.sales_refresh_interval_secs must be at least 60".to_string()));
+        }
+        
+        // Validate redirects
+        for (name, pattern) in [
+            ("redirects.product_path", &self.redirects.product_path),
+            ("redirects.category_path", &self.redirects.category_path),
+        ] {
+            if !pattern.starts_with('/') || pattern.matches("{slug}").count() != 1 {
+                return Err(Error::Config(format!("{} must be a path containing {{slug}} once", name)));
+            }
+        }
+        if self.redirects.cache_ttl_secs == 0 {
+            return Err(Error::Config("redirects.cache_ttl_secs must be positive".to_string()));
+        }
+        
+        // Validate business metrics and alerts
+        self.observability.validate()?;
+        
+        // Validate storefront analytics
+        self.analytics.validate()?;
+        
+        // Validate the embedded admin UI
+        self.admin_ui.validate()?;
+        
+        // Validate admin live events
+        if self.websocket.enabled && self.websocket.broadcast_buffer_size == 0 {
+            return Err(Error::Config("websocket.broadcast_buffer_size must be positive".to_string()));
+        }
+        if self.websocket.enabled && self.websocket.ping_interval_secs == 0 {
+            return Err(Error::Config("websocket.ping_interval_secs must be positive".to_string()));
+        }
+        
+        // Validate the domain event bus
+        self.events.validate()?;
+        
+        // Validate media storage and image processing
+        self.media.validate()?;
+        
+        // Validate hosted checkout
+        let hosted_checkout = &self.payment.hosted_checkout;
+        if !(30..=1440).contains(&hosted_checkout.expires_after_mins) {
+            return Err(Error::Config("payment.hosted_checkout.expires_after_mins must be between 30 and 1440".to_string()));
+        }
+        for url in hosted_checkout.success_url.iter().chain(hosted_checkout.cancel_url.iter()) {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(Error::Config("payment.hosted_checkout redirect URLs must be http(s) URLs".to_string()));
+            }
+        }
+        
+        // Validate 3-D Secure challenges
+        let three_d_secure = &self.payment.three_d_secure;
+        if !(5..=120).contains(&three_d_secure.challenge_expires_mins) {
+            return Err(Error::Config("payment.three_d_secure.challenge_expires_mins must be between 5 and 120".to_string()));
+        }
+        for url in std::iter::once(&three_d_secure.public_url).chain(three_d_secure.return_url.iter()) {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(Error::Config("payment.three_d_secure URLs must be http(s) URLs".to_string()));
+            }
+        }
+        
+        // Validate captures
+        let capture = &self.payment.capture;
+        if !(1..=30).contains(&capture.authorization_valid_days) {
+            return Err(Error::Config("payment.capture.authorization_valid_days must be between 1 and 30".to_string()));
+        }
+        if capture.expiry_check_interval_secs < 60 {
+            return Err(Error::Config("payment.capture.expiry_check_interval_secs must be at least 60".to_string()));
+        }
+        
+        // Validate payout reconciliation
+        let reconciliation = &self.payment.reconciliation;
+        if reconciliation.enabled {
+            if reconciliation.gateways.is_empty() {
+                return Err(Error::Config("payment.reconciliation.gateways must not be empty".to_string()));
+            }
+            if !(1..=90).contains(&reconciliation.lookback_days) {
+                return Err(Error::Config("payment.reconciliation.lookback_days must be between 1 and 90".to_string()));
+            }
+            if reconciliation.amount_tolerance < rust_decimal::Decimal::ZERO {
+                return Err(Error::Config("payment.reconciliation.amount_tolerance must not be negative".to_string()));
+            }
+            if reconciliation.interval_secs < 300 {
+                return Err(Error::Config("payment.reconciliation.interval_secs must be at least 300".to_string()));
+            }
+        }
+        
+        // Validate wallets
+        let apple_pay = &self.payment.wallets.apple_pay;
+        if apple_pay.merchant_identity_cert.is_some() != apple_pay.merchant_identity_key.is_some() {
+            return Err(Error::Config("payment.wallets.apple_pay needs both merchant_identity_cert and merchant_identity_key".to_string()));
+        }
+        if apple_pay.merchant_identity_cert.is_some() && (apple_pay.merchant_id.is_none() || apple_pay.domains.is_empty()) {
+            return Err(Error::Config("payment.wallets.apple_pay merchant validation needs merchant_id and domains".to_string()));
+        }
+        
+        // Validate email provider
+        let email = &self.notifications.email;
+        match email.provider {
+            EmailProvider::SendGrid if email.sendgrid.as_ref().map_or(true, |sg| sg.api_key.is_empty()) => {
+                return Err(Error::Config("notifications.email.sendgrid.api_key is required with provider = \"sendgrid\"".to_string()));
+            }
+            EmailProvider::Ses if email.ses.as_ref().map_or(true, |ses| ses.region.is_empty()) => {
+                return Err(Error::Config("notifications.email.ses.region is required with provider = \"ses\"".to_string()));
+            }
+            _ => {}
+        }
+        if !is_hex_color(&email.branding.brand_color) {
+            return Err(Error::Config("notifications.email.branding.brand_color must be a #rgb or #rrggbb color".to_string()));
+        }
+        if email.branding.company_name.trim().is_empty() {
+            return Err(Error::Config("notifications.email.branding.company_name must be set".to_string()));
+        }
+        let unsubscribe_url = &self.notifications.unsubscribe.public_url;
+        if !unsubscribe_url.starts_with("https://") && !unsubscribe_url.starts_with("http://") {
+            return Err(Error::Config("notifications.unsubscribe.public_url must be an http(s) URL".to_string()));
+        }
+        if self.notifications.unsubscribe.link_ttl_days <= 0 {
+            return Err(Error::Config("notifications.unsubscribe.link_ttl_days must be positive".to_string()));
+        }
+        
         // Validate JWT secret
         if self.security.jwt.secret.is_empty() {
             return Err(Error::Config(
@@ -120,6 +651,9 @@ impl Config {
                 "JWT secret must be at least 32 bytes long".to_string()
             ));
         }
+        self.security.jwt.validate()?;
+        self.security.two_factor.validate()?;
+        self.security.oauth.validate()?;
         
         Ok(())
     }
@@ -144,6 +678,14 @@ pub struct ServerConfig {
     
     #[serde(default)]
     pub limits: LimitsConfig,
+
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Reverse proxies (addresses or CIDR networks) whose `X-Forwarded-For`
+    /// and `X-Real-IP` headers name the client; other peers are the client
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
 }
 
 impl Default for ServerConfig {
@@ -155,6 +697,8 @@ impl Default for ServerConfig {
             graceful_shutdown_timeout_secs: default_graceful_shutdown(),
             cors: CorsConfig::default(),
             limits: LimitsConfig::default(),
+            compression: CompressionConfig::default(),
+            trusted_proxies: Vec::new(),
         }
     }
 }
@@ -205,7 +749,7 @@ impl Default for CorsConfig {
                 .into_iter()
                 .map(String::from)
                 .collect(),
-            allowed_headers: vec!["Content-Type", "Authorization", "X-Requested-With"]
+            allowed_headers: vec!["Content-Type", "Authorization", "X-Requested-With", "Idempotency-Key", "X-CSRF-Token"]
                 .into_iter()
                 .map(String::from)
                 .collect(),
@@ -249,6 +793,33 @@ fn default_burst() -> u64 {
     200
 }
 
+/// Response compression (`[server.compression]`)
+///
+/// Responses are gzip or brotli compressed per `Accept-Encoding`. Images
+/// and event streams are never compressed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Smaller responses are sent uncompressed
+    #[serde(default = "default_compression_min_bytes")]
+    pub min_size_bytes: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size_bytes: default_compression_min_bytes(),
+        }
+    }
+}
+
+fn default_compression_min_bytes() -> u16 {
+    1024
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
     #[serde(default = "default_db_type")]
@@ -269,13 +840,35 @@ pub struct DatabaseConfig {
     #[serde(default)]
     pub ssl_mode: SslMode,
     
-
+    /// Replicas for read-only queries (product listing and search, reports)
+    #[serde(default)]
+    pub read_replicas: Vec<ReadReplicaConfig>,
+    
+    /// Seconds a replica that failed to connect is skipped
+    #[serde(default = "default_replica_retry_secs")]
+    pub replica_retry_secs: u64,
 }
 
-impl DatabaseConfig {
-    /// Build database connection URL
-    pub fn url(&self) -> String {
-        format!(
+/// A read replica; unset fields are taken from the primary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadReplicaConfig {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub database: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub pool_size: Option<u32>,
+}
+
+impl DatabaseConfig {
+    /// Build database connection URL
+    pub fn url(&self) -> String {
+        format!(
             "postgres://{}:{}@{}:{}/{}",
             self.username, self.password, self.host, self.port, self.database
         )
@@ -293,7 +886,8 @@ impl Default for DatabaseConfig {
             password: "password".to_string(),
             pool_size: default_pool_size(),
             ssl_mode: SslMode::default(),
-
+            read_replicas: Vec::new(),
+            replica_retry_secs: default_replica_retry_secs(),
         }
     }
 }
@@ -315,6 +909,10 @@ fn default_pool_size() -> u32 {
     20
 }
 
+fn default_replica_retry_secs() -> u64 {
+    30
+}
+
 
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
@@ -389,6 +987,14 @@ pub struct CacheConfig {
     
     #[serde(default = "default_redis_pool_size")]
     pub redis_pool_size: u32,
+    
+    /// Cache warming run on startup before the server reports ready
+    #[serde(default)]
+    pub warmup: CacheWarmupConfig,
+    
+    /// Per-route response caching and Cache-Control hints
+    #[serde(default)]
+    pub responses: ResponseCacheConfig,
 }
 
 impl Default for CacheConfig {
@@ -398,10 +1004,132 @@ impl Default for CacheConfig {
             max_size_mb: default_cache_max_size(),
             redis_url: None,
             redis_pool_size: default_redis_pool_size(),
+            warmup: CacheWarmupConfig::default(),
+            responses: ResponseCacheConfig::default(),
+        }
+    }
+}
+
+/// Cache warming configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheWarmupConfig {
+    /// Warm caches on startup (readiness stays false until done)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Number of best-selling products to pre-load
+    #[serde(default = "default_warmup_top_products")]
+    pub top_products: i64,
+    
+    /// TTL for warmed Redis entries in seconds
+    #[serde(default = "default_warmup_ttl_secs")]
+    pub ttl_secs: u64,
+    
+    /// Re-analyze search tables whose statistics are older than this
+    #[serde(default = "default_warmup_search_max_age_hours")]
+    pub search_max_age_hours: i64,
+    
+    /// Give up warming and report ready after this many seconds
+    #[serde(default = "default_warmup_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for CacheWarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            top_products: default_warmup_top_products(),
+            ttl_secs: default_warmup_ttl_secs(),
+            search_max_age_hours: default_warmup_search_max_age_hours(),
+            timeout_secs: default_warmup_timeout_secs(),
+        }
+    }
+}
+
+/// Per-route response caching
+///
+/// GET responses of routes matching a rule get the rule's `Cache-Control`
+/// and a `Surrogate-Key` header, which CDNs and the demo frontend server use
+/// to cache and purge them. `public` responses are also kept in memory for
+/// `ttl_secs`. Staff with `settings:write` skip the cache by sending
+/// `bypass_header`, and purge it by surrogate key or URL pattern under
+/// `/api/v1/admin/cache/purge`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// Apply the rules (no Cache-Control hints and no caching when false)
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Most responses kept in memory
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+    
+    /// Request header staff send to skip (and refresh) cached responses
+    #[serde(default = "default_response_cache_bypass_header")]
+    pub bypass_header: String,
+    
+    /// Rules by route; the first matching rule applies
+    #[serde(default)]
+    pub rules: Vec<CachePolicyRule>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_response_cache_max_entries(),
+            bypass_header: default_response_cache_bypass_header(),
+            rules: Vec::new(),
         }
     }
 }
 
+/// Cache policy of the routes matching `path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachePolicyRule {
+    /// Request path under the server root, e.g. `/api/v1/products/:id`;
+    /// `:name` matches one segment and a trailing `*` the rest of the path
+    pub path: String,
+    
+    /// Seconds responses may be cached (0 sends `no-cache`)
+    #[serde(default = "default_cache_policy_ttl_secs")]
+    pub ttl_secs: u64,
+    
+    /// Who may cache responses
+    #[serde(default)]
+    pub visibility: CacheVisibility,
+    
+    /// Surrogate keys of responses, for purging; `{name}` is replaced with
+    /// the path's `:name` segment, e.g. `product:{id}`
+    #[serde(default)]
+    pub surrogate_keys: Vec<String>,
+}
+
+/// Who may cache a response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheVisibility {
+    /// Shared caches (CDNs, this server's response cache) may keep it
+    #[default]
+    Public,
+    /// Only the client's own cache may keep it
+    Private,
+    /// Nothing may keep it
+    NoStore,
+}
+
+fn default_response_cache_max_entries() -> usize {
+    10_000
+}
+
+fn default_response_cache_bypass_header() -> String {
+    "X-Cache-Bypass".to_string()
+}
+
+fn default_cache_policy_ttl_secs() -> u64 {
+    60
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CacheType {
     Memory,
@@ -420,6 +1148,22 @@ fn default_redis_pool_size() -> u32 {
     10
 }
 
+fn default_warmup_top_products() -> i64 {
+    50
+}
+
+fn default_warmup_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_warmup_search_max_age_hours() -> i64 {
+    24
+}
+
+fn default_warmup_timeout_secs() -> u64 {
+    60
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
     #[serde(default = "default_api_key_prefix_length")]
@@ -430,6 +1174,12 @@ pub struct SecurityConfig {
     
     #[serde(default)]
     pub jwt: JwtConfig,
+    
+    #[serde(default)]
+    pub two_factor: TwoFactorConfig,
+    
+    #[serde(default)]
+    pub oauth: OAuthConfig,
 }
 
 impl Default for SecurityConfig {
@@ -438,6 +1188,8 @@ impl Default for SecurityConfig {
             api_key_prefix_length: default_api_key_prefix_length(),
             api_key_secret_length: default_api_secret_length(),
             jwt: JwtConfig::default(),
+            two_factor: TwoFactorConfig::default(),
+            oauth: OAuthConfig::default(),
         }
     }
 }
@@ -450,13 +1202,33 @@ fn default_api_secret_length() -> usize {
     32
 }
 
+/// Access tokens, and the refresh tokens of login sessions
+///
+/// Access tokens stop working when their login session is revoked; keep them
+/// short-lived (`access_token_ttl_mins`) and let clients renew them at
+/// `/auth/refresh`.
+/// Each refresh rotates the refresh token; a login session ends when it goes
+/// unused for `refresh_token_idle_days` or `refresh_token_ttl_days` after login.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtConfig {
     #[serde(default = "default_jwt_secret")]
     pub secret: String,
     
+    /// Access token lifetime in hours, unless `access_token_ttl_mins` is set
     #[serde(default = "default_jwt_expiry")]
     pub expiry_hours: u64,
+    
+    /// Access token lifetime in minutes; overrides `expiry_hours`
+    #[serde(default)]
+    pub access_token_ttl_mins: Option<u64>,
+    
+    /// Days after login when a session ends however often it is refreshed
+    #[serde(default = "default_refresh_token_ttl_days")]
+    pub refresh_token_ttl_days: u64,
+    
+    /// Days without a refresh after which a session ends
+    #[serde(default = "default_refresh_token_idle_days")]
+    pub refresh_token_idle_days: u64,
 }
 
 impl Default for JwtConfig {
@@ -464,8 +1236,282 @@ impl Default for JwtConfig {
         Self {
             secret: default_jwt_secret(),
             expiry_hours: default_jwt_expiry(),
+            access_token_ttl_mins: None,
+            refresh_token_ttl_days: default_refresh_token_ttl_days(),
+            refresh_token_idle_days: default_refresh_token_idle_days(),
+        }
+    }
+}
+
+impl JwtConfig {
+    /// Access token lifetime in seconds
+    pub fn access_token_ttl_secs(&self) -> i64 {
+        match self.access_token_ttl_mins {
+            Some(mins) => mins as i64 * 60,
+            None => self.expiry_hours as i64 * 3600,
+        }
+    }
+    
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.access_token_ttl_secs() <= 0 {
+            return Err(Error::Config("security.jwt access token lifetime must be positive".to_string()));
+        }
+        if self.refresh_token_ttl_days == 0 || self.refresh_token_idle_days == 0 {
+            return Err(Error::Config("security.jwt refresh token lifetimes must be positive".to_string()));
+        }
+        if self.refresh_token_idle_days > self.refresh_token_ttl_days {
+            return Err(Error::Config(
+                "security.jwt.refresh_token_idle_days must not exceed refresh_token_ttl_days".to_string(),
+            ));
+        }
+        if self.access_token_ttl_secs() >= self.refresh_token_idle_days as i64 * 86400 {
+            return Err(Error::Config(
+                "security.jwt access tokens must expire before refresh_token_idle_days".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Two-factor authentication with authenticator app (TOTP) codes
+///
+/// Customers and staff opt in at `/auth/2fa/enroll`; from then on login asks
+/// for a code after the password. With `required_for_admins`, the admin API
+/// refuses staff who haven't enabled it, or whose token predates enabling it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorConfig {
+    /// Shown in authenticator apps next to the account email
+    #[serde(default = "default_two_factor_issuer")]
+    pub issuer: String,
+    
+    #[serde(default)]
+    pub required_for_admins: bool,
+    
+    /// Seconds a login has to send its code after the password
+    #[serde(default = "default_two_factor_challenge_ttl_secs")]
+    pub challenge_ttl_secs: u64,
+    
+    /// Wrong codes accepted per login before it must start over
+    #[serde(default = "default_two_factor_max_challenge_attempts")]
+    pub max_challenge_attempts: u32,
+    
+    /// Wrong codes in a row after which the account's 2FA is locked
+    #[serde(default = "default_two_factor_max_failed_attempts")]
+    pub max_failed_attempts: u32,
+    
+    #[serde(default = "default_two_factor_lockout_mins")]
+    pub lockout_mins: u64,
+    
+    /// One-time backup codes issued at enrollment
+    #[serde(default = "default_two_factor_backup_codes")]
+    pub backup_codes: usize,
+    
+    /// 30-second steps of clock drift tolerated either way
+    #[serde(default = "default_two_factor_allowed_drift_steps")]
+    pub allowed_drift_steps: u32,
+}
+
+impl Default for TwoFactorConfig {
+    fn default() -> Self {
+        Self {
+            issuer: default_two_factor_issuer(),
+            required_for_admins: false,
+            challenge_ttl_secs: default_two_factor_challenge_ttl_secs(),
+            max_challenge_attempts: default_two_factor_max_challenge_attempts(),
+            max_failed_attempts: default_two_factor_max_failed_attempts(),
+            lockout_mins: default_two_factor_lockout_mins(),
+            backup_codes: default_two_factor_backup_codes(),
+            allowed_drift_steps: default_two_factor_allowed_drift_steps(),
+        }
+    }
+}
+
+impl TwoFactorConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.issuer.trim().is_empty() || self.issuer.contains(':') {
+            return Err(Error::Config("security.two_factor.issuer must be set and not contain ':'".to_string()));
+        }
+        if self.challenge_ttl_secs < 30 || self.max_challenge_attempts == 0 || self.max_failed_attempts == 0 {
+            return Err(Error::Config(
+                "security.two_factor.challenge_ttl_secs must be at least 30 and the attempt limits positive".to_string(),
+            ));
+        }
+        if self.lockout_mins == 0 || !(1..=20).contains(&self.backup_codes) || self.allowed_drift_steps > 2 {
+            return Err(Error::Config(
+                "security.two_factor needs a positive lockout_mins, 1 - 20 backup_codes and allowed_drift_steps of at most 2".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn default_two_factor_issuer() -> String {
+    "R Commerce".to_string()
+}
+
+fn default_two_factor_challenge_ttl_secs() -> u64 {
+    300
+}
+
+fn default_two_factor_max_challenge_attempts() -> u32 {
+    5
+}
+
+fn default_two_factor_max_failed_attempts() -> u32 {
+    10
+}
+
+fn default_two_factor_lockout_mins() -> u64 {
+    15
+}
+
+fn default_two_factor_backup_codes() -> usize {
+    10
+}
+
+fn default_two_factor_allowed_drift_steps() -> u32 {
+    1
+}
+
+/// Social login with Google, Apple and GitHub (OAuth 2.0 / OpenID Connect)
+///
+/// A provider is enabled by its section. Its `redirect_uri` is this server's
+/// `/api/v1/auth/oauth/<provider>/callback`, as registered with the provider.
+/// After the callback the browser returns to the storefront's `return_to`,
+/// which must start with one of `allowed_return_urls`, carrying a one-time
+/// login code for `/auth/oauth/login` or `/auth/oauth/session`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    #[serde(default)]
+    pub allowed_return_urls: Vec<String>,
+    
+    /// Link a provider account to the customer with the same email, if the
+    /// provider verified it
+    #[serde(default = "default_true")]
+    pub link_by_email: bool,
+    
+    /// Create a customer on the first login of an unknown, verified email
+    #[serde(default = "default_true")]
+    pub create_customers: bool,
+    
+    /// Seconds a login may take at the provider
+    #[serde(default = "default_oauth_state_ttl_secs")]
+    pub state_ttl_secs: u64,
+    
+    #[serde(default)]
+    pub google: Option<OAuthProviderConfig>,
+    
+    #[serde(default)]
+    pub apple: Option<OAuthProviderConfig>,
+    
+    #[serde(default)]
+    pub github: Option<OAuthProviderConfig>,
+}
+
+/// An identity provider's client registration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProviderConfig {
+    pub client_id: String,
+    
+    /// Not used by Apple, whose client secret is signed with `private_key_path`
+    #[serde(default)]
+    pub client_secret: String,
+    
+    pub redirect_uri: String,
+    
+    /// Scopes to request; empty for the provider's defaults
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    
+    /// Apple developer team ID
+    #[serde(default)]
+    pub team_id: Option<String>,
+    
+    /// ID of the Sign in with Apple key
+    #[serde(default)]
+    pub key_id: Option<String>,
+    
+    /// The Sign in with Apple key (.p8)
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+}
+
+impl Default for OAuthConfig {
+    fn default() -> Self {
+        Self {
+            allowed_return_urls: Vec::new(),
+            link_by_email: true,
+            create_customers: true,
+            state_ttl_secs: default_oauth_state_ttl_secs(),
+            google: None,
+            apple: None,
+            github: None,
+        }
+    }
+}
+
+impl OAuthConfig {
+    /// Client registration of a provider, if it is enabled
+    pub fn provider(&self, provider: crate::models::OAuthProvider) -> Option<&OAuthProviderConfig> {
+        use crate::models::OAuthProvider;
+        
+        match provider {
+            OAuthProvider::Google => self.google.as_ref(),
+            OAuthProvider::Apple => self.apple.as_ref(),
+            OAuthProvider::Github => self.github.as_ref(),
         }
     }
+    
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::models::OAuthProvider;
+        use crate::Error;
+        
+        let enabled: Vec<OAuthProvider> = OAuthProvider::ALL.into_iter().filter(|p| self.provider(*p).is_some()).collect();
+        if enabled.is_empty() {
+            return Ok(());
+        }
+        if self.allowed_return_urls.is_empty() {
+            return Err(Error::Config("security.oauth.allowed_return_urls must list the storefront URLs logins return to".to_string()));
+        }
+        if let Some(url) = self.allowed_return_urls.iter().find(|url| !is_http_url(url)) {
+            return Err(Error::Config(format!("security.oauth.allowed_return_urls: '{}' is not an http(s) URL", url)));
+        }
+        if self.state_ttl_secs < 60 {
+            return Err(Error::Config("security.oauth.state_ttl_secs must be at least 60".to_string()));
+        }
+        for provider in enabled {
+            let config = self.provider(provider).expect("enabled provider");
+            if config.client_id.trim().is_empty() || !is_http_url(&config.redirect_uri) {
+                return Err(Error::Config(format!(
+                    "security.oauth.{} needs a client_id and an http(s) redirect_uri",
+                    provider
+                )));
+            }
+            let has_secret = match provider {
+                OAuthProvider::Apple => config.team_id.is_some() && config.key_id.is_some() && config.private_key_path.is_some(),
+                _ => !config.client_secret.is_empty(),
+            };
+            if !has_secret {
+                return Err(Error::Config(match provider {
+                    OAuthProvider::Apple => "security.oauth.apple needs team_id, key_id and private_key_path".to_string(),
+                    _ => format!("security.oauth.{} needs a client_secret", provider),
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    url::Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+fn default_oauth_state_ttl_secs() -> u64 {
+    600
 }
 
 fn default_jwt_secret() -> String {
@@ -478,6 +1524,14 @@ fn default_jwt_expiry() -> u64 {
     24 // hours
 }
 
+fn default_refresh_token_ttl_days() -> u64 {
+    30
+}
+
+fn default_refresh_token_idle_days() -> u64 {
+    14
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaConfig {
     #[serde(default = "default_storage_type")]
@@ -490,8 +1544,18 @@ pub struct MediaConfig {
     pub s3_bucket: Option<String>,
     pub s3_access_key: Option<String>,
     pub s3_secret_key: Option<String>,
+    /// S3-compatible service (MinIO, R2, ...); objects are addressed by path
     pub s3_endpoint: Option<String>,
     
+    /// Base URL stored objects are served from (e.g. a CDN); defaults to
+    /// `local_base_url` or the bucket URL
+    #[serde(default)]
+    pub public_url: Option<String>,
+    
+    /// Largest product image upload or download accepted
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: usize,
+    
     #[serde(default)]
     pub image_processing: ImageProcessingConfig,
 }
@@ -507,11 +1571,66 @@ impl Default for MediaConfig {
             s3_access_key: None,
             s3_secret_key: None,
             s3_endpoint: None,
+            public_url: None,
+            max_upload_bytes: default_max_upload_bytes(),
             image_processing: ImageProcessingConfig::default(),
         }
     }
 }
 
+impl MediaConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.max_upload_bytes == 0 {
+            return Err(Error::Config("media.max_upload_bytes must be at least 1".to_string()));
+        }
+        if let StorageType::S3 = self.storage_type {
+            let missing = [
+                ("s3_bucket", &self.s3_bucket),
+                ("s3_region", &self.s3_region),
+                ("s3_access_key", &self.s3_access_key),
+                ("s3_secret_key", &self.s3_secret_key),
+            ]
+            .iter()
+            .find(|(_, value)| value.as_deref().map_or(true, str::is_empty))
+            .map(|(name, _)| *name);
+            if let Some(name) = missing {
+                return Err(Error::Config(format!("media.{} is required when media.storage_type = \"S3\"", name)));
+            }
+        }
+        
+        let processing = &self.image_processing;
+        if !(1..=100).contains(&processing.default_quality) {
+            return Err(Error::Config("media.image_processing.default_quality must be between 1 and 100".to_string()));
+        }
+        if processing.enabled && processing.command.trim().is_empty() {
+            return Err(Error::Config("media.image_processing.command is required when image processing is enabled".to_string()));
+        }
+        for (i, size) in processing.sizes.iter().enumerate() {
+            let valid_name = !size.name.is_empty()
+                && size.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(Error::Config(format!(
+                    "media.image_processing.sizes: '{}' is not a valid size name (letters, digits, - and _)",
+                    size.name
+                )));
+            }
+            if size.width == 0 || size.height == 0 {
+                return Err(Error::Config(format!("media.image_processing.sizes.{} needs a width and height", size.name)));
+            }
+            if processing.sizes[..i].iter().any(|other| other.name == size.name) {
+                return Err(Error::Config(format!("media.image_processing.sizes.{} is defined twice", size.name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_max_upload_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum StorageType {
     Local,
@@ -534,6 +1653,10 @@ pub struct ImageProcessingConfig {
     
     #[serde(default = "default_image_sizes")]
     pub sizes: Vec<ImageSize>,
+    
+    /// ImageMagick (or compatible) command that resizes product images
+    #[serde(default = "default_image_command")]
+    pub command: String,
 }
 
 impl Default for ImageProcessingConfig {
@@ -542,10 +1665,15 @@ impl Default for ImageProcessingConfig {
             enabled: true,
             default_quality: default_image_quality(),
             sizes: default_image_sizes(),
+            command: default_image_command(),
         }
     }
 }
 
+fn default_image_command() -> String {
+    "magick".to_string()
+}
+
 fn default_image_quality() -> u8 {
     85
 }
@@ -559,6 +1687,8 @@ fn default_image_sizes() -> Vec<ImageSize> {
     ]
 }
 
+/// A product image variant: fits within `width` x `height`, or fills it
+/// exactly (cut to the centre) with `crop`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageSize {
     pub name: String,
@@ -577,6 +1707,9 @@ pub struct NotificationConfig {
     
     #[serde(default)]
     pub sms: SmsConfig,
+    
+    #[serde(default)]
+    pub unsubscribe: UnsubscribeConfig,
 }
 
 impl Default for NotificationConfig {
@@ -585,12 +1718,38 @@ impl Default for NotificationConfig {
             enabled: true,
             email: EmailConfig::default(),
             sms: SmsConfig::default(),
+            unsubscribe: UnsubscribeConfig::default(),
+        }
+    }
+}
+
+/// One-click unsubscribe links in marketing emails
+///
+/// Links are signed with a key derived from `security.jwt.secret`, so
+/// rotating it invalidates the links in emails already sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnsubscribeConfig {
+    /// Base URL of this API for unsubscribe links
+    pub public_url: String,
+    /// Days a link keeps working after the email is sent
+    pub link_ttl_days: i64,
+}
+
+impl Default for UnsubscribeConfig {
+    fn default() -> Self {
+        Self {
+            public_url: "http://localhost:8080".to_string(),
+            link_ttl_days: 90,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailConfig {
+    /// Provider used to send email
+    #[serde(default)]
+    pub provider: EmailProvider,
     #[serde(default)]
     pub smtp_host: Option<String>,
     pub smtp_port: Option<u16>,
@@ -600,11 +1759,25 @@ pub struct EmailConfig {
     pub from_email: Option<String>,
     #[serde(default = "default_true")]
     pub smtp_tls: bool,
+    /// Settings for `provider = "sendgrid"`
+    #[serde(default)]
+    pub sendgrid: Option<SendGridConfig>,
+    /// Settings for `provider = "ses"`
+    #[serde(default)]
+    pub ses: Option<SesConfig>,
+    /// Directory of template overrides (`<type>.subject.txt`, `<type>.txt`,
+    /// `<type>.html`) and the `layout.html` they are wrapped in
+    #[serde(default)]
+    pub templates_dir: Option<PathBuf>,
+    /// Store identity shared by all emails
+    #[serde(default)]
+    pub branding: EmailBranding,
 }
 
 impl Default for EmailConfig {
     fn default() -> Self {
         Self {
+            provider: EmailProvider::default(),
             smtp_host: None,
             smtp_port: None,
             smtp_user: None,
@@ -612,12 +1785,124 @@ impl Default for EmailConfig {
             from_name: None,
             from_email: None,
             smtp_tls: true,
+            sendgrid: None,
+            ses: None,
+            templates_dir: None,
+            branding: EmailBranding::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct SmsConfig {
+/// Branding available to every email template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailBranding {
+    pub company_name: String,
+    pub support_email: String,
+    /// Replaces the text logo when set
+    pub logo_url: Option<String>,
+    /// Accent color (`#rgb` or `#rrggbb`)
+    pub brand_color: String,
+    /// Extra line shown in the footer (address, legal notice...)
+    pub footer_text: Option<String>,
+}
+
+impl Default for EmailBranding {
+    fn default() -> Self {
+        Self {
+            company_name: "R Commerce".to_string(),
+            support_email: "support@rcommerce.local".to_string(),
+            logo_url: None,
+            brand_color: "#EB4F27".to_string(),
+            footer_text: None,
+        }
+    }
+}
+
+impl EmailBranding {
+    /// Template variables for the branding; unset values are empty
+    pub fn variables(&self) -> [(&'static str, String); 5] {
+        [
+            ("company_name", self.company_name.clone()),
+            ("support_email", self.support_email.clone()),
+            ("logo_url", self.logo_url.clone().unwrap_or_default()),
+            ("brand_color", self.brand_color.clone()),
+            ("footer_text", self.footer_text.clone().unwrap_or_default()),
+        ]
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Email sending provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProvider {
+    #[default]
+    Smtp,
+    SendGrid,
+    Ses,
+    /// Log emails instead of sending them
+    Mock,
+}
+
+impl EmailProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailProvider::Smtp => "smtp",
+            EmailProvider::SendGrid => "sendgrid",
+            EmailProvider::Ses => "ses",
+            EmailProvider::Mock => "mock",
+        }
+    }
+}
+
+/// SendGrid Web API settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendGridConfig {
+    pub api_key: String,
+    /// Base64 public key of the signed Event Webhook; events are rejected
+    /// without a valid signature when set
+    #[serde(default)]
+    pub webhook_public_key: Option<String>,
+    #[serde(default = "default_sendgrid_api_url")]
+    pub api_url: String,
+}
+
+fn default_sendgrid_api_url() -> String {
+    "https://api.sendgrid.com".to_string()
+}
+
+/// Amazon SES (API v2) settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SesConfig {
+    pub region: String,
+    /// Falls back to AWS_ACCESS_KEY_ID
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// Falls back to AWS_SECRET_ACCESS_KEY
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+    /// Falls back to AWS_SESSION_TOKEN
+    #[serde(default)]
+    pub session_token: Option<String>,
+    /// Configuration set whose event destination publishes to SNS
+    #[serde(default)]
+    pub configuration_set: Option<String>,
+    /// Token the SNS subscription URL must carry (`?token=...`)
+    #[serde(default)]
+    pub webhook_token: Option<String>,
+    /// Override the API endpoint (e.g. a VPC endpoint)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SmsConfig {
     #[serde(default)]
     pub provider: Option<String>,
     pub twilio_account_sid: Option<String>,
@@ -656,6 +1941,11 @@ pub struct RateLimitConfig {
     #[serde(default = "default_rate_limit_api_key")]
     pub api_key_requests_per_minute: u32,
     
+    /// Maximum requests per minute with one publishable storefront key,
+    /// unless the key sets its own limit
+    #[serde(default = "default_rate_limit_publishable_key")]
+    pub publishable_key_requests_per_minute: u32,
+    
     /// Blocklist of IP addresses
     #[serde(default)]
     pub blocklist: Vec<String>,
@@ -691,6 +1981,7 @@ impl Default for RateLimitConfig {
             max_concurrent_per_ip: 10,
             api_key_limiting: true,
             api_key_requests_per_minute: 1000,
+            publishable_key_requests_per_minute: 300,
             blocklist: vec![],
             allowlist: vec![],
             ddos_protection: true,
@@ -709,6 +2000,7 @@ fn default_rate_limit_hour() -> u32 { 1000 }
 fn default_rate_limit_day() -> u32 { 10000 }
 fn default_max_concurrent() -> u32 { 10 }
 fn default_rate_limit_api_key() -> u32 { 1000 }
+fn default_rate_limit_publishable_key() -> u32 { 300 }
 
 /// Feature flags
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -726,15 +2018,2718 @@ pub struct FeatureFlags {
     pub experimental: bool,
 }
 
-impl Default for FeatureFlags {
-    fn default() -> Self {
-        Self {
-            debug_api: true,
-            metrics: true,
-            health_check: true,
-            experimental: false,
-        }
-    }
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            debug_api: true,
+            metrics: true,
+            health_check: true,
+            experimental: false,
+        }
+    }
+}
+
+/// Traffic capture for debugging production-only issues
+/// 
+/// When enabled, a sampled share of request/response pairs is recorded
+/// (with credentials and PII redacted) into an in-memory ring buffer that
+/// admins can download and replay against staging with `rcommerce replay`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Fraction of requests to capture (0.0 - 1.0)
+    #[serde(default = "default_capture_sample_rate")]
+    pub sample_rate: f64,
+    
+    /// Number of exchanges kept in the ring buffer
+    #[serde(default = "default_capture_buffer_size")]
+    pub buffer_size: usize,
+    
+    /// Request/response bodies are truncated to this many bytes
+    #[serde(default = "default_capture_max_body_bytes")]
+    pub max_body_bytes: usize,
+    
+    /// Extra JSON field names to redact, on top of the built-in PII list
+    #[serde(default)]
+    pub redact_fields: Vec<String>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: default_capture_sample_rate(),
+            buffer_size: default_capture_buffer_size(),
+            max_body_bytes: default_capture_max_body_bytes(),
+            redact_fields: Vec::new(),
+        }
+    }
+}
+
+fn default_capture_sample_rate() -> f64 {
+    0.01
+}
+
+fn default_capture_buffer_size() -> usize {
+    500
+}
+
+fn default_capture_max_body_bytes() -> usize {
+    16 * 1024
+}
+
+/// Access log for debugging partner integrations
+/// 
+/// Records one row per API request (method, path, status, latency, API key
+/// prefix and truncated client IP) in the `access_log` table, separate from
+/// the application logs. Query strings and bodies are never stored, and
+/// client IPs are cut to their network (IPv4 /24, IPv6 /48) before writing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Entries older than this are deleted
+    #[serde(default = "default_access_log_retention_days")]
+    pub retention_days: u32,
+    
+    /// Path prefixes not logged (health checks, metrics scrapes)
+    #[serde(default = "default_access_log_exclude_paths")]
+    pub exclude_paths: Vec<String>,
+    
+    /// Entries waiting to be written; further requests go unlogged when full
+    #[serde(default = "default_access_log_buffer_size")]
+    pub buffer_size: usize,
+    
+    /// How often buffered entries are written
+    #[serde(default = "default_access_log_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: default_access_log_retention_days(),
+            exclude_paths: default_access_log_exclude_paths(),
+            buffer_size: default_access_log_buffer_size(),
+            flush_interval_secs: default_access_log_flush_interval_secs(),
+        }
+    }
+}
+
+impl AccessLogConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        if self.retention_days == 0 || self.retention_days > 365 {
+            return Err(crate::Error::Config("access_log.retention_days must be between 1 and 365".to_string()));
+        }
+        if self.buffer_size == 0 || self.flush_interval_secs == 0 {
+            return Err(crate::Error::Config(
+                "access_log.buffer_size and access_log.flush_interval_secs must be positive".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn default_access_log_retention_days() -> u32 {
+    30
+}
+
+fn default_access_log_exclude_paths() -> Vec<String> {
+    vec!["/health".to_string(), "/ready".to_string(), "/metrics".to_string()]
+}
+
+fn default_access_log_buffer_size() -> usize {
+    10_000
+}
+
+fn default_access_log_flush_interval_secs() -> u64 {
+    5
+}
+
+/// Audit log of API mutations
+/// 
+/// Records every POST, PUT, PATCH and DELETE outside `exclude_paths` in the
+/// append-only `audit_log` table: the acting user or API key, the resource,
+/// the status, the full client IP and, for resources that can be
+/// snapshotted, the fields the call changed with their old and new values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Entries older than this are deleted
+    #[serde(default = "default_audit_retention_days")]
+    pub retention_days: u32,
+    
+    /// Path prefixes not audited (shopper carts and checkout, login)
+    #[serde(default = "default_audit_exclude_paths")]
+    pub exclude_paths: Vec<String>,
+    
+    /// Extra field names whose values are masked, on top of credentials
+    #[serde(default)]
+    pub redact_fields: Vec<String>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: default_audit_retention_days(),
+            exclude_paths: default_audit_exclude_paths(),
+            redact_fields: Vec::new(),
+        }
+    }
+}
+
+impl AuditConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        if self.retention_days < 30 || self.retention_days > 3650 {
+            return Err(crate::Error::Config("audit.retention_days must be between 30 and 3650".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_audit_retention_days() -> u32 {
+    2555
+}
+
+fn default_audit_exclude_paths() -> Vec<String> {
+    vec![
+        "/api/v1/auth".to_string(),
+        "/api/v1/carts".to_string(),
+        "/api/v1/checkout".to_string(),
+        "/api/v1/analytics".to_string(),
+    ]
+}
+
+/// Soft deletes of products, customers and orders
+/// 
+/// Deleted records are hidden from lookups and listings but can be restored
+/// until they are purged, `retention_days` after deletion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftDeleteConfig {
+    /// Days a deleted record stays restorable; 0 keeps deleted records forever
+    #[serde(default = "default_soft_delete_retention_days")]
+    pub retention_days: u32,
+    
+    /// Records of each kind removed per purge run
+    #[serde(default = "default_soft_delete_purge_batch_size")]
+    pub purge_batch_size: i64,
+}
+
+impl Default for SoftDeleteConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_soft_delete_retention_days(),
+            purge_batch_size: default_soft_delete_purge_batch_size(),
+        }
+    }
+}
+
+fn default_soft_delete_retention_days() -> u32 {
+    30
+}
+
+fn default_soft_delete_purge_batch_size() -> i64 {
+    500
+}
+
+/// Price display configuration
+/// 
+/// Built-in rules cover the common store locales and the supported
+/// currencies; entries here override individual fields of those rules or
+/// add new locales and currencies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormattingConfig {
+    /// Locale used when a request, email or document doesn't specify one
+    #[serde(default = "default_formatting_locale")]
+    pub default_locale: String,
+    
+    /// Number format overrides keyed by locale (e.g. "de-DE")
+    #[serde(default)]
+    pub locales: std::collections::HashMap<String, LocaleFormatOverride>,
+    
+    /// Currency overrides keyed by ISO code (e.g. "JPY")
+    #[serde(default)]
+    pub currencies: std::collections::HashMap<String, CurrencyFormatOverride>,
+}
+
+impl Default for FormattingConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: default_formatting_locale(),
+            locales: std::collections::HashMap::new(),
+            currencies: std::collections::HashMap::new(),
+        }
+    }
+}
+
+fn default_formatting_locale() -> String {
+    "en-US".to_string()
+}
+
+/// Per-locale number format override
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleFormatOverride {
+    pub decimal_separator: Option<String>,
+    pub group_separator: Option<String>,
+    /// "before" or "after" the amount
+    pub symbol_position: Option<crate::services::formatting_service::SymbolPosition>,
+    /// Put a space between the symbol and the amount
+    pub symbol_spacing: Option<bool>,
+}
+
+/// Per-currency display override
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CurrencyFormatOverride {
+    pub symbol: Option<String>,
+    /// Number of decimal places to display
+    pub decimals: Option<u32>,
+}
+
+/// Where client countries are looked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GeoIpProvider {
+    /// Country header set by the CDN or load balancer (e.g. Cloudflare's CF-IPCountry)
+    #[default]
+    Header,
+    /// HTTP lookup API returning JSON with a country code field
+    Api,
+}
+
+/// IP geolocation configuration
+/// 
+/// Detects the client's country to default the storefront currency and
+/// locale, prefill the country for tax and shipping estimates, and block
+/// sanctioned regions. Storefronts read (and override) the result through
+/// `/api/v1/geo`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoIpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    #[serde(default)]
+    pub provider: GeoIpProvider,
+    
+    /// Header holding the ISO country code (header provider); only read on
+    /// requests from `server.trusted_proxies`
+    #[serde(default = "default_geoip_country_header")]
+    pub country_header: String,
+    
+    /// Lookup URL with an `{ip}` placeholder (api provider)
+    #[serde(default)]
+    pub api_url: Option<String>,
+    
+    /// JSON field holding the ISO country code in the API response
+    #[serde(default = "default_geoip_api_country_field")]
+    pub api_country_field: String,
+    
+    /// API lookup timeout in milliseconds
+    #[serde(default = "default_geoip_api_timeout_ms")]
+    pub api_timeout_ms: u64,
+    
+    /// How long API lookups are cached per IP, in seconds
+    #[serde(default = "default_geoip_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    
+    /// Currency used when the country is unknown or has no mapping
+    #[serde(default = "default_geoip_default_currency")]
+    pub default_currency: String,
+    
+    /// Country to currency overrides on top of the built-in mapping (e.g. CH = "EUR")
+    #[serde(default)]
+    pub country_currencies: std::collections::HashMap<String, String>,
+    
+    /// ISO country codes refused with 451 Unavailable For Legal Reasons
+    #[serde(default)]
+    pub blocked_countries: Vec<String>,
+}
+
+impl Default for GeoIpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: GeoIpProvider::default(),
+            country_header: default_geoip_country_header(),
+            api_url: None,
+            api_country_field: default_geoip_api_country_field(),
+            api_timeout_ms: default_geoip_api_timeout_ms(),
+            cache_ttl_secs: default_geoip_cache_ttl_secs(),
+            default_currency: default_geoip_default_currency(),
+            country_currencies: std::collections::HashMap::new(),
+            blocked_countries: Vec::new(),
+        }
+    }
+}
+
+fn default_geoip_country_header() -> String {
+    "CF-IPCountry".to_string()
+}
+
+fn default_geoip_api_country_field() -> String {
+    "country_code".to_string()
+}
+
+fn default_geoip_api_timeout_ms() -> u64 {
+    1500
+}
+
+fn default_geoip_cache_ttl_secs() -> u64 {
+    86400
+}
+
+fn default_geoip_default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Order statuses that are final and may be archived
+pub const ARCHIVABLE_ORDER_STATUSES: &[&str] = &["completed", "cancelled", "refunded"];
+
+/// Order auto-archival configuration
+/// 
+/// Finished orders older than `archive_after_days` are moved, together with
+/// their items, payments, refunds, fulfillments and notes, out of the hot
+/// `orders` table into `orders_archive`. Detail endpoints read through to the
+/// archive, so archived orders stay visible to customers and admins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderArchiveConfig {
+    /// Run archival periodically while the API server is up
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Archive orders finished more than this many days ago
+    #[serde(default = "default_archive_after_days")]
+    pub archive_after_days: u32,
+    
+    /// Order statuses eligible for archival
+    #[serde(default = "default_archive_statuses")]
+    pub statuses: Vec<String>,
+    
+    /// Orders moved per transaction
+    #[serde(default = "default_archive_batch_size")]
+    pub batch_size: u32,
+    
+    /// Seconds between archival runs
+    #[serde(default = "default_archive_interval_secs")]
+    pub interval_secs: u64,
+    
+    /// Directory to also export archived orders to as JSON Lines (cold storage)
+    #[serde(default)]
+    pub export_dir: Option<PathBuf>,
+}
+
+impl Default for OrderArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            archive_after_days: default_archive_after_days(),
+            statuses: default_archive_statuses(),
+            batch_size: default_archive_batch_size(),
+            interval_secs: default_archive_interval_secs(),
+            export_dir: None,
+        }
+    }
+}
+
+fn default_archive_after_days() -> u32 {
+    365
+}
+
+fn default_archive_statuses() -> Vec<String> {
+    vec!["completed".to_string()]
+}
+
+fn default_archive_batch_size() -> u32 {
+    500
+}
+
+fn default_archive_interval_secs() -> u64 {
+    3600
+}
+
+/// Webhook replay configuration
+/// 
+/// Limits for re-sending an endpoint's outbound webhook events after a
+/// consumer outage (`rcommerce webhook replay` and
+/// POST /api/v1/admin/webhooks/:id/replays).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookReplayConfig {
+    /// Deliveries per second when a replay does not set a rate
+    #[serde(default = "default_replay_rate_per_second")]
+    pub default_rate_per_second: u32,
+    
+    /// Highest rate a replay may request
+    #[serde(default = "default_replay_max_rate_per_second")]
+    pub max_rate_per_second: u32,
+    
+    /// Longest time range a single replay may cover
+    #[serde(default = "default_replay_max_range_days")]
+    pub max_range_days: u32,
+    
+    /// Events loaded per query while replaying
+    #[serde(default = "default_replay_batch_size")]
+    pub batch_size: u32,
+    
+    /// Timeout for each delivery
+    #[serde(default = "default_replay_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookReplayConfig {
+    fn default() -> Self {
+        Self {
+            default_rate_per_second: default_replay_rate_per_second(),
+            max_rate_per_second: default_replay_max_rate_per_second(),
+            max_range_days: default_replay_max_range_days(),
+            batch_size: default_replay_batch_size(),
+            timeout_secs: default_replay_timeout_secs(),
+        }
+    }
+}
+
+fn default_replay_rate_per_second() -> u32 {
+    5
+}
+
+fn default_replay_max_rate_per_second() -> u32 {
+    50
+}
+
+fn default_replay_max_range_days() -> u32 {
+    31
+}
+
+fn default_replay_batch_size() -> u32 {
+    100
+}
+
+fn default_replay_timeout_secs() -> u64 {
+    30
+}
+
+/// Subscription billing configuration
+/// 
+/// Renewal runs invoice subscriptions that are due (including trials that
+/// have ended), charge the saved payment method and hand failed renewals to
+/// dunning (`[dunning]`), whose due retries are charged in the same run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionBillingConfig {
+    /// Register the `subscription_billing` recurring job (see `[scheduler]`)
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Seconds between renewal runs; sets the `subscription_billing` job's
+    /// schedule when it is first registered (change it later with
+    /// `rcommerce jobs schedule`)
+    #[serde(default = "default_billing_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for SubscriptionBillingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_billing_interval_secs(),
+        }
+    }
+}
+
+fn default_billing_interval_secs() -> u64 {
+    900
+}
+
+/// Manual stock adjustment approval configuration
+/// 
+/// Adjustments at or above either threshold wait for a second staff member
+/// with one of `approver_roles` to approve them; smaller ones are posted as
+/// stock movements straight away. Leave both thresholds unset to post every
+/// adjustment immediately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockAdjustmentConfig {
+    /// Require approval when the change is at least this many units
+    #[serde(default = "default_adjustment_threshold_units")]
+    pub approval_threshold_units: Option<u32>,
+    
+    /// Require approval when the change is at least this percentage of the
+    /// current stock level
+    #[serde(default = "default_adjustment_threshold_percent")]
+    pub approval_threshold_percent: Option<u32>,
+    
+    /// Roles allowed to approve or reject adjustments
+    #[serde(default = "default_adjustment_approver_roles")]
+    pub approver_roles: Vec<crate::models::CustomerRole>,
+    
+    /// Let approvers approve adjustments they requested themselves
+    #[serde(default)]
+    pub allow_self_approval: bool,
+    
+    /// Queue a notification to each approver when an adjustment needs approval
+    #[serde(default = "default_true")]
+    pub notify_approvers: bool,
+}
+
+impl Default for StockAdjustmentConfig {
+    fn default() -> Self {
+        Self {
+            approval_threshold_units: default_adjustment_threshold_units(),
+            approval_threshold_percent: default_adjustment_threshold_percent(),
+            approver_roles: default_adjustment_approver_roles(),
+            allow_self_approval: false,
+            notify_approvers: true,
+        }
+    }
+}
+
+fn default_adjustment_threshold_units() -> Option<u32> {
+    Some(50)
+}
+
+fn default_adjustment_threshold_percent() -> Option<u32> {
+    Some(25)
+}
+
+fn default_adjustment_approver_roles() -> Vec<crate::models::CustomerRole> {
+    vec![crate::models::CustomerRole::Admin]
+}
+
+/// Purchase orders and reorder alerts
+///
+/// The `reorder` job alerts staff with `alert_roles` once about each
+/// inventory level at or below its `reorder_point`, and with `auto_draft`
+/// adds `reorder_quantity` of it to a draft purchase order for its
+/// preferred supplier (levels with enough stock incoming are skipped).
+/// Drafts are only sent to suppliers once staff submit them.
+///
+/// Placed orders are sent by the `po_dispatch` job: emailed with a link to
+/// acknowledge them under `public_url`, or uploaded as CSV with
+/// `sftp_command`. The job also alerts `alert_roles` about orders not
+/// acknowledged within `ack_overdue_hours` and orders past their expected
+/// date; acknowledgments and deliveries that differ from the order by more
+/// than `price_tolerance_percent` (or in quantity) are flagged to them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchasingConfig {
+    /// Seconds between runs of the `reorder` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_reorder_interval_secs")]
+    pub reorder_interval_secs: u64,
+    
+    /// Draft purchase orders for low stock
+    #[serde(default)]
+    pub auto_draft: bool,
+    
+    /// Queue a low stock notification to each staff member with `alert_roles`
+    #[serde(default = "default_true")]
+    pub notify_low_stock: bool,
+    
+    /// Roles notified about low stock, discrepancies and late orders
+    #[serde(default = "default_reorder_alert_roles")]
+    pub alert_roles: Vec<crate::models::CustomerRole>,
+    
+    /// Seconds between runs of the `po_dispatch` job, when it is first
+    /// registered
+    #[serde(default = "default_dispatch_interval_secs")]
+    pub dispatch_interval_secs: u64,
+    
+    /// Orders that failed to send this many times are left for staff
+    #[serde(default = "default_max_dispatch_attempts")]
+    pub max_dispatch_attempts: i32,
+    
+    /// Alert staff about sent orders unacknowledged this long (0 never)
+    #[serde(default = "default_ack_overdue_hours")]
+    pub ack_overdue_hours: i64,
+    
+    /// Confirmed or invoiced unit costs within this percentage of the
+    /// order's are not discrepancies
+    #[serde(default)]
+    pub price_tolerance_percent: rust_decimal::Decimal,
+    
+    /// Base URL of this API, for the acknowledgment links in emailed orders
+    #[serde(default = "default_purchasing_public_url")]
+    pub public_url: String,
+    
+    /// OpenSSH-compatible `sftp` client used for SFTP dispatch
+    #[serde(default = "default_sftp_command")]
+    pub sftp_command: String,
+    
+    /// Private key for SFTP dispatch (otherwise the client's default)
+    #[serde(default)]
+    pub sftp_identity_file: Option<String>,
+    
+    /// Timeout of one SFTP upload
+    #[serde(default = "default_sftp_timeout_secs")]
+    pub sftp_timeout_secs: u64,
+}
+
+impl Default for PurchasingConfig {
+    fn default() -> Self {
+        Self {
+            reorder_interval_secs: default_reorder_interval_secs(),
+            auto_draft: false,
+            notify_low_stock: true,
+            alert_roles: default_reorder_alert_roles(),
+            dispatch_interval_secs: default_dispatch_interval_secs(),
+            max_dispatch_attempts: default_max_dispatch_attempts(),
+            ack_overdue_hours: default_ack_overdue_hours(),
+            price_tolerance_percent: rust_decimal::Decimal::ZERO,
+            public_url: default_purchasing_public_url(),
+            sftp_command: default_sftp_command(),
+            sftp_identity_file: None,
+            sftp_timeout_secs: default_sftp_timeout_secs(),
+        }
+    }
+}
+
+fn default_reorder_interval_secs() -> u64 {
+    3600
+}
+
+fn default_reorder_alert_roles() -> Vec<crate::models::CustomerRole> {
+    vec![crate::models::CustomerRole::Manager, crate::models::CustomerRole::Admin]
+}
+
+fn default_dispatch_interval_secs() -> u64 {
+    300
+}
+
+fn default_max_dispatch_attempts() -> i32 {
+    5
+}
+
+fn default_ack_overdue_hours() -> i64 {
+    48
+}
+
+fn default_purchasing_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_sftp_command() -> String {
+    "sftp".to_string()
+}
+
+fn default_sftp_timeout_secs() -> u64 {
+    60
+}
+
+/// Supplier stock and price feeds, and dropship order routing
+///
+/// The `supplier_feeds` job pulls each active feed with a source URL once
+/// its own `pull_interval_minutes` have passed; feeds can also be pushed to
+/// `POST /api/v1/supplier-feeds/:supplier_id`. The `dropship` job routes
+/// paid order lines of dropshipping suppliers' products to them and
+/// retries orders that failed to send, up to `max_send_attempts` times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplierFeedsConfig {
+    /// Seconds between runs of the `supplier_feeds` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_supplier_feed_interval_secs")]
+    pub feed_interval_secs: u64,
+    
+    /// Seconds between runs of the `dropship` job, when it is first
+    /// registered
+    #[serde(default = "default_dropship_interval_secs")]
+    pub dropship_interval_secs: u64,
+    
+    /// Timeout of feed downloads and order webhooks
+    #[serde(default = "default_supplier_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    
+    /// Largest feed file accepted, pulled or pushed
+    #[serde(default = "default_max_feed_bytes")]
+    pub max_feed_bytes: usize,
+    
+    /// Dropship orders that failed this many times are left for staff
+    #[serde(default = "default_max_send_attempts")]
+    pub max_send_attempts: i32,
+}
+
+impl Default for SupplierFeedsConfig {
+    fn default() -> Self {
+        Self {
+            feed_interval_secs: default_supplier_feed_interval_secs(),
+            dropship_interval_secs: default_dropship_interval_secs(),
+            request_timeout_secs: default_supplier_request_timeout_secs(),
+            max_feed_bytes: default_max_feed_bytes(),
+            max_send_attempts: default_max_send_attempts(),
+        }
+    }
+}
+
+impl SupplierFeedsConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.feed_interval_secs < 60 || self.dropship_interval_secs < 60 {
+            return Err(Error::Config(
+                "supplier_feeds.feed_interval_secs and dropship_interval_secs must be at least 60".to_string()
+            ));
+        }
+        if self.request_timeout_secs == 0 {
+            return Err(Error::Config("supplier_feeds.request_timeout_secs must be positive".to_string()));
+        }
+        if !(1024..=crate::inventory::supplier_feed::MAX_FEED_BYTES).contains(&self.max_feed_bytes) {
+            return Err(Error::Config(format!(
+                "supplier_feeds.max_feed_bytes must be between 1024 and {}",
+                crate::inventory::supplier_feed::MAX_FEED_BYTES
+            )));
+        }
+        if self.max_send_attempts < 1 {
+            return Err(Error::Config("supplier_feeds.max_send_attempts must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_supplier_feed_interval_secs() -> u64 {
+    300
+}
+
+fn default_dropship_interval_secs() -> u64 {
+    120
+}
+
+fn default_supplier_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_feed_bytes() -> usize {
+    20 * 1024 * 1024
+}
+
+fn default_max_send_attempts() -> i32 {
+    5
+}
+
+/// Shipment planning across warehouses and backorders
+///
+/// Orders ship complete (held until every item is in stock) or partial
+/// (what is in stock ships now, from as few locations as possible) by
+/// the order's preference, else the customer's, else
+/// `default_shipping_preference`. The `backorders` job ships what is left
+/// of orders once it is back in stock; receiving a purchase order also
+/// triggers it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FulfillmentConfig {
+    /// Preference of orders and customers without one
+    #[serde(default)]
+    pub default_shipping_preference: crate::order::ShippingPreference,
+    
+    /// Seconds between runs of the `backorders` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_backorder_interval_secs")]
+    pub backorder_interval_secs: u64,
+    
+    /// Tell customers when their order ships in more than one parcel or
+    /// some of it is backordered, and when backordered items ship
+    #[serde(default = "default_true")]
+    pub notify_customers: bool,
+}
+
+impl Default for FulfillmentConfig {
+    fn default() -> Self {
+        Self {
+            default_shipping_preference: crate::order::ShippingPreference::default(),
+            backorder_interval_secs: default_backorder_interval_secs(),
+            notify_customers: true,
+        }
+    }
+}
+
+fn default_backorder_interval_secs() -> u64 {
+    900
+}
+
+/// Duties and import taxes quoted at checkout for cross-border orders
+///
+/// The built-in estimator looks up duty by destination country and HS
+/// code prefix (longest match wins) and charges none on goods worth no
+/// more than the destination's de minimis value. Customers choose DDP
+/// (duties and taxes prepaid at checkout) or DAP (paid on delivery).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandedCostConfig {
+    /// Quote landed cost for orders shipping abroad
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// ISO country orders ship from (default the `[shipping] origin` country)
+    #[serde(default)]
+    pub origin_country: Option<String>,
+    
+    /// Let customers prepay duties and taxes at checkout
+    #[serde(default = "default_true")]
+    pub offer_ddp: bool,
+    
+    /// Incoterm of orders whose customer does not choose one
+    #[serde(default)]
+    pub default_incoterm: crate::tax::Incoterm,
+    
+    /// Handling fee added to DDP orders
+    #[serde(default)]
+    pub ddp_fee: rust_decimal::Decimal,
+    
+    /// Duty rates by destination country and HS code prefix
+    #[serde(default)]
+    pub duty_rates: Vec<DutyRateConfig>,
+    
+    /// Goods value per destination country at or below which no duty is due
+    #[serde(default)]
+    pub de_minimis: std::collections::HashMap<String, rust_decimal::Decimal>,
+    
+    /// Import VAT/GST rate per destination country (EU countries default
+    /// to their standard VAT rate)
+    #[serde(default)]
+    pub import_tax_rates: std::collections::HashMap<String, rust_decimal::Decimal>,
+}
+
+impl Default for LandedCostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            origin_country: None,
+            offer_ddp: true,
+            default_incoterm: crate::tax::Incoterm::default(),
+            ddp_fee: rust_decimal::Decimal::ZERO,
+            duty_rates: Vec::new(),
+            de_minimis: std::collections::HashMap::new(),
+            import_tax_rates: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl LandedCostConfig {
+    /// Rates must be fractions, HS prefixes digits and amounts not negative
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        use rust_decimal::Decimal;
+        
+        let is_rate = |rate: &Decimal| *rate >= Decimal::ZERO && *rate <= Decimal::ONE;
+        for duty in &self.duty_rates {
+            if duty.country.trim().is_empty() || !is_rate(&duty.rate) {
+                return Err(Error::Config(
+                    "landed_cost.duty_rates need a country and a rate between 0 and 1".to_string()
+                ));
+            }
+            if !duty.hs_code.chars().all(|c| c.is_ascii_digit()) {
+                return Err(Error::Config(format!(
+                    "landed_cost.duty_rates hs_code '{}' must be digits",
+                    duty.hs_code
+                )));
+            }
+        }
+        if let Some((country, _)) = self.import_tax_rates.iter().find(|(_, rate)| !is_rate(rate)) {
+            return Err(Error::Config(format!(
+                "landed_cost.import_tax_rates.{} must be between 0 and 1",
+                country
+            )));
+        }
+        if self.ddp_fee < Decimal::ZERO || self.de_minimis.values().any(|value| *value < Decimal::ZERO) {
+            return Err(Error::Config("landed_cost.ddp_fee and de_minimis must not be negative".to_string()));
+        }
+        if !self.offer_ddp && self.default_incoterm == crate::tax::Incoterm::Ddp {
+            return Err(Error::Config("landed_cost.default_incoterm is DDP but offer_ddp is false".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Fraud screening of checkout orders
+///
+/// The built-in scorers add points for many orders from one buyer or IP
+/// address in a short window, for billing and shipping addresses in
+/// different countries, and for a first order over a value. The total
+/// (capped at 100) picks the action: the most severe whose score is reached.
+/// Leave an action's score unset to never take it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraudConfig {
+    /// Screen orders at checkout
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Score from which payments need 3-D Secure
+    #[serde(default = "default_fraud_require_3ds_score")]
+    pub require_3ds_score: Option<i32>,
+    
+    /// Score from which orders are held for review
+    #[serde(default = "default_fraud_review_score")]
+    pub review_score: Option<i32>,
+    
+    /// Score from which orders are cancelled
+    #[serde(default)]
+    pub cancel_score: Option<i32>,
+    
+    /// Window of the velocity check
+    #[serde(default = "default_fraud_velocity_window_minutes")]
+    pub velocity_window_minutes: u32,
+    
+    /// Orders by one customer, email or IP address allowed in the window
+    #[serde(default = "default_fraud_velocity_max_orders")]
+    pub velocity_max_orders: u32,
+    
+    #[serde(default = "default_fraud_velocity_score")]
+    pub velocity_score: i32,
+    
+    /// Points for billing and shipping addresses in different countries
+    #[serde(default = "default_fraud_country_mismatch_score")]
+    pub country_mismatch_score: i32,
+    
+    /// Order total above which a buyer's first order is high value
+    #[serde(default = "default_fraud_high_value_amount")]
+    pub high_value_amount: rust_decimal::Decimal,
+    
+    #[serde(default = "default_fraud_high_value_score")]
+    pub high_value_score: i32,
+}
+
+fn default_fraud_require_3ds_score() -> Option<i32> {
+    Some(30)
+}
+
+fn default_fraud_review_score() -> Option<i32> {
+    Some(50)
+}
+
+fn default_fraud_velocity_window_minutes() -> u32 {
+    60
+}
+
+fn default_fraud_velocity_max_orders() -> u32 {
+    3
+}
+
+fn default_fraud_velocity_score() -> i32 {
+    40
+}
+
+fn default_fraud_country_mismatch_score() -> i32 {
+    25
+}
+
+fn default_fraud_high_value_amount() -> rust_decimal::Decimal {
+    rust_decimal::Decimal::from(500)
+}
+
+fn default_fraud_high_value_score() -> i32 {
+    30
+}
+
+impl Default for FraudConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            require_3ds_score: default_fraud_require_3ds_score(),
+            review_score: default_fraud_review_score(),
+            cancel_score: None,
+            velocity_window_minutes: default_fraud_velocity_window_minutes(),
+            velocity_max_orders: default_fraud_velocity_max_orders(),
+            velocity_score: default_fraud_velocity_score(),
+            country_mismatch_score: default_fraud_country_mismatch_score(),
+            high_value_amount: default_fraud_high_value_amount(),
+            high_value_score: default_fraud_high_value_score(),
+        }
+    }
+}
+
+impl FraudConfig {
+    /// Action for a risk score
+    pub fn action(&self, score: i32) -> crate::models::RiskAction {
+        use crate::models::RiskAction;
+        
+        let reached = |threshold: Option<i32>| threshold.is_some_and(|threshold| score >= threshold);
+        if reached(self.cancel_score) {
+            RiskAction::Cancel
+        } else if reached(self.review_score) {
+            RiskAction::Review
+        } else if reached(self.require_3ds_score) {
+            RiskAction::Require3ds
+        } else {
+            RiskAction::Allow
+        }
+    }
+    
+    /// Scores are 1-100 and rise with the action's severity
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        let thresholds = [self.require_3ds_score, self.review_score, self.cancel_score];
+        if thresholds.iter().flatten().any(|score| !(1..=crate::models::MAX_RISK_SCORE).contains(score)) {
+            return Err(Error::Config(
+                "fraud.require_3ds_score, review_score and cancel_score must be between 1 and 100".to_string()
+            ));
+        }
+        let set: Vec<i32> = thresholds.iter().flatten().copied().collect();
+        if set.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(Error::Config(
+                "fraud.require_3ds_score, review_score and cancel_score must rise in that order".to_string()
+            ));
+        }
+        if self.velocity_window_minutes == 0 || self.velocity_max_orders == 0 {
+            return Err(Error::Config(
+                "fraud.velocity_window_minutes and velocity_max_orders must be positive".to_string()
+            ));
+        }
+        if self.velocity_score < 0 || self.country_mismatch_score < 0 || self.high_value_score < 0
+            || self.high_value_amount < rust_decimal::Decimal::ZERO
+        {
+            return Err(Error::Config("fraud scores and high_value_amount must not be negative".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Printer profiles of warehouse print stations
+///
+/// Print batches merge the shipping labels or packing slips of many
+/// shipments into one PDF laid out for a profile's paper. A profile with a
+/// `print_command` can be printed straight from `rcommerce shipping
+/// print-batch --print`; the PDF's path is appended to the command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintingConfig {
+    /// Profile of batches of shipping labels
+    #[serde(default = "default_label_profile")]
+    pub label_profile: String,
+    
+    /// Profile of batches of packing slips and other documents
+    #[serde(default = "default_document_profile")]
+    pub document_profile: String,
+    
+    #[serde(default = "default_printer_profiles")]
+    pub profiles: Vec<PrinterProfile>,
+    
+    /// Most shipments in one batch
+    #[serde(default = "default_print_batch_size")]
+    pub max_batch_size: usize,
+}
+
+impl Default for PrintingConfig {
+    fn default() -> Self {
+        Self {
+            label_profile: default_label_profile(),
+            document_profile: default_document_profile(),
+            profiles: default_printer_profiles(),
+            max_batch_size: default_print_batch_size(),
+        }
+    }
+}
+
+impl PrintingConfig {
+    /// The profile named `name`
+    pub fn profile(&self, name: &str) -> Option<&PrinterProfile> {
+        self.profiles.iter().find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+    
+    /// Profile names must be unique and the default profiles defined
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        let mut seen = std::collections::HashSet::new();
+        for profile in &self.profiles {
+            if profile.name.trim().is_empty() || !seen.insert(profile.name.to_ascii_lowercase()) {
+                return Err(Error::Config(format!(
+                    "printing.profiles need unique names ('{}')",
+                    profile.name
+                )));
+            }
+        }
+        for name in [&self.label_profile, &self.document_profile] {
+            if self.profile(name).is_none() {
+                return Err(Error::Config(format!("printing profile '{}' is not defined under [[printing.profiles]]", name)));
+            }
+        }
+        if self.max_batch_size == 0 {
+            return Err(Error::Config("printing.max_batch_size must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// A printer and the paper it takes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrinterProfile {
+    pub name: String,
+    
+    pub paper: PaperSize,
+    
+    /// Command that prints a PDF, e.g. "lp -d zebra-1"
+    #[serde(default)]
+    pub print_command: Option<String>,
+}
+
+/// Paper a printer takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperSize {
+    /// 4x6 inch thermal labels
+    #[serde(rename = "label_4x6")]
+    Label4x6,
+    A4,
+    Letter,
+}
+
+fn default_label_profile() -> String {
+    "label".to_string()
+}
+
+fn default_document_profile() -> String {
+    "a4".to_string()
+}
+
+fn default_printer_profiles() -> Vec<PrinterProfile> {
+    vec![
+        PrinterProfile { name: "label".to_string(), paper: PaperSize::Label4x6, print_command: None },
+        PrinterProfile { name: "a4".to_string(), paper: PaperSize::A4, print_command: None },
+    ]
+}
+
+fn default_print_batch_size() -> usize {
+    200
+}
+
+/// Duty charged by a destination country on goods under an HS code prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DutyRateConfig {
+    /// ISO country code of the destination
+    pub country: String,
+    
+    /// HS code prefix, e.g. "6109" for cotton T-shirts (empty for all goods)
+    #[serde(default)]
+    pub hs_code: String,
+    
+    /// Duty as a fraction of the customs value
+    pub rate: rust_decimal::Decimal,
+}
+
+/// Where FX rates come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FxProvider {
+    /// Rates recorded through /api/v1/admin/exchange-rates
+    #[default]
+    Manual,
+    /// European Central Bank daily reference rates
+    Ecb,
+}
+
+/// Currency conversion configuration
+/// 
+/// Products without a price list price in the shopper's currency are
+/// converted from their own price, and order totals in other currencies
+/// are converted back to `base_currency`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxConfig {
+    #[serde(default)]
+    pub provider: FxProvider,
+    
+    /// Currency the store reports in
+    #[serde(default = "default_fx_base_currency")]
+    pub base_currency: String,
+    
+    /// ECB daily reference rate feed (ecb provider)
+    #[serde(default = "default_fx_ecb_url")]
+    pub ecb_url: String,
+    
+    /// How long fetched rates are cached, in seconds (ecb provider)
+    #[serde(default = "default_fx_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    
+    /// Rate fetch timeout in seconds (ecb provider)
+    #[serde(default = "default_fx_timeout_secs")]
+    pub timeout_secs: u64,
+    
+    /// Record fetched rates in the exchange rate history, so order and
+    /// payment rate snapshots use them (ecb provider)
+    #[serde(default = "default_true")]
+    pub record_fetched_rates: bool,
+}
+
+impl FxConfig {
+    /// The base currency (validated on load)
+    pub fn base_currency(&self) -> crate::models::Currency {
+        self.base_currency.parse().unwrap_or_default()
+    }
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self {
+            provider: FxProvider::default(),
+            base_currency: default_fx_base_currency(),
+            ecb_url: default_fx_ecb_url(),
+            cache_ttl_secs: default_fx_cache_ttl_secs(),
+            timeout_secs: default_fx_timeout_secs(),
+            record_fetched_rates: true,
+        }
+    }
+}
+
+fn default_fx_base_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_fx_ecb_url() -> String {
+    "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml".to_string()
+}
+
+fn default_fx_cache_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_fx_timeout_secs() -> u64 {
+    10
+}
+
+/// Delivery instructions and scheduled delivery configuration
+/// 
+/// Times are store-local, `utc_offset_minutes` from UTC. Orders placed after
+/// `cutoff_time` (or on a non-shipping day) are dispatched on the next
+/// shipping day; customers can book delivery from `min_lead_days` after
+/// dispatch up to `max_days_ahead` from today, into one of `windows`, on
+/// carrier services that support scheduled delivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryConfig {
+    /// Same-day dispatch cutoff, "HH:MM"
+    #[serde(default = "default_delivery_cutoff_time")]
+    pub cutoff_time: String,
+    
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    
+    /// Days from dispatch to the earliest bookable delivery date
+    #[serde(default = "default_delivery_min_lead_days")]
+    pub min_lead_days: u32,
+    
+    /// Latest bookable delivery date, in days from today
+    #[serde(default = "default_delivery_max_days_ahead")]
+    pub max_days_ahead: u32,
+    
+    #[serde(default)]
+    pub ship_on_weekends: bool,
+    
+    #[serde(default)]
+    pub deliver_on_weekends: bool,
+    
+    /// Bookable time windows, "HH:MM-HH:MM"; empty books whole days only
+    #[serde(default = "default_delivery_windows")]
+    pub windows: Vec<String>,
+    
+    /// Dates with no dispatch or delivery (holidays), "YYYY-MM-DD"
+    #[serde(default)]
+    pub blackout_dates: Vec<chrono::NaiveDate>,
+    
+    #[serde(default = "default_delivery_max_instructions_length")]
+    pub max_instructions_length: usize,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            cutoff_time: default_delivery_cutoff_time(),
+            utc_offset_minutes: 0,
+            min_lead_days: default_delivery_min_lead_days(),
+            max_days_ahead: default_delivery_max_days_ahead(),
+            ship_on_weekends: false,
+            deliver_on_weekends: false,
+            windows: default_delivery_windows(),
+            blackout_dates: Vec::new(),
+            max_instructions_length: default_delivery_max_instructions_length(),
+        }
+    }
+}
+
+fn default_delivery_cutoff_time() -> String {
+    "14:00".to_string()
+}
+
+fn default_delivery_min_lead_days() -> u32 {
+    1
+}
+
+fn default_delivery_max_days_ahead() -> u32 {
+    21
+}
+
+fn default_delivery_windows() -> Vec<String> {
+    vec!["08:00-12:00".to_string(), "12:00-17:00".to_string(), "17:00-21:00".to_string()]
+}
+
+fn default_delivery_max_instructions_length() -> usize {
+    250
+}
+
+/// Returns (RMA) configuration
+/// 
+/// Customers can request returns for `window_days` after ordering. Approved
+/// returns get a prepaid label from `label_provider` (default: the shipping
+/// default provider) to `address` (default: the shipping origin); items are
+/// refunded to the original payment when the return is received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnsConfig {
+    /// Days after ordering that returns can be requested (0 = no limit)
+    #[serde(default = "default_returns_window_days")]
+    pub window_days: u32,
+    
+    #[serde(default)]
+    pub label_provider: Option<String>,
+    
+    /// Carrier service code for return labels (default: the provider's first domestic service)
+    #[serde(default)]
+    pub label_service: Option<String>,
+    
+    /// Parcel weight used for return labels
+    #[serde(default = "default_returns_label_weight")]
+    pub label_weight: rust_decimal::Decimal,
+    
+    #[serde(default = "default_returns_label_weight_unit")]
+    pub label_weight_unit: String,
+    
+    /// Where returns are shipped to
+    #[serde(default)]
+    pub address: Option<ShippingOriginConfig>,
+    
+    /// Email customers when their return is approved, rejected or refunded
+    #[serde(default = "default_true")]
+    pub notify_customers: bool,
+}
+
+impl Default for ReturnsConfig {
+    fn default() -> Self {
+        Self {
+            window_days: default_returns_window_days(),
+            label_provider: None,
+            label_service: None,
+            label_weight: default_returns_label_weight(),
+            label_weight_unit: default_returns_label_weight_unit(),
+            address: None,
+            notify_customers: true,
+        }
+    }
+}
+
+fn default_returns_window_days() -> u32 {
+    30
+}
+
+fn default_returns_label_weight() -> rust_decimal::Decimal {
+    rust_decimal::Decimal::ONE
+}
+
+fn default_returns_label_weight_unit() -> String {
+    "lb".to_string()
+}
+
+/// Custom checkout fields
+/// 
+/// Extra fields shown at checkout, in order, as `[[checkout_fields.fields]]`
+/// entries. Answers are validated against them and stored on the order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckoutFieldsConfig {
+    #[serde(default)]
+    pub fields: Vec<crate::models::CheckoutFieldDefinition>,
+}
+
+/// Gift card configuration
+/// 
+/// Cards are issued for one of `denominations` or, if `allow_custom_amount`,
+/// any amount from `min_custom_amount` to `max_custom_amount`. Cards expire
+/// `expiry_days` after issue (0 = never) unless issued with their own
+/// expiry; expired balances are written off every `expiry_interval_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCardsConfig {
+    /// Currency cards are issued in unless another is given
+    #[serde(default = "default_gift_cards_currency")]
+    pub currency: String,
+    
+    #[serde(default = "default_gift_cards_denominations")]
+    pub denominations: Vec<rust_decimal::Decimal>,
+    
+    #[serde(default = "default_true")]
+    pub allow_custom_amount: bool,
+    
+    #[serde(default = "default_gift_cards_min_custom_amount")]
+    pub min_custom_amount: rust_decimal::Decimal,
+    
+    #[serde(default = "default_gift_cards_max_custom_amount")]
+    pub max_custom_amount: rust_decimal::Decimal,
+    
+    #[serde(default = "default_gift_cards_expiry_days")]
+    pub expiry_days: u32,
+    
+    #[serde(default = "default_gift_cards_expiry_interval_secs")]
+    pub expiry_interval_secs: u64,
+}
+
+impl Default for GiftCardsConfig {
+    fn default() -> Self {
+        Self {
+            currency: default_gift_cards_currency(),
+            denominations: default_gift_cards_denominations(),
+            allow_custom_amount: true,
+            min_custom_amount: default_gift_cards_min_custom_amount(),
+            max_custom_amount: default_gift_cards_max_custom_amount(),
+            expiry_days: default_gift_cards_expiry_days(),
+            expiry_interval_secs: default_gift_cards_expiry_interval_secs(),
+        }
+    }
+}
+
+fn default_gift_cards_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_gift_cards_denominations() -> Vec<rust_decimal::Decimal> {
+    [25, 50, 100].into_iter().map(rust_decimal::Decimal::from).collect()
+}
+
+fn default_gift_cards_min_custom_amount() -> rust_decimal::Decimal {
+    rust_decimal::Decimal::from(5)
+}
+
+fn default_gift_cards_max_custom_amount() -> rust_decimal::Decimal {
+    rust_decimal::Decimal::from(500)
+}
+
+fn default_gift_cards_expiry_days() -> u32 {
+    0
+}
+
+fn default_gift_cards_expiry_interval_secs() -> u64 {
+    3600
+}
+
+/// Flash sale configuration
+/// 
+/// Flash sales need Redis. An admitted customer holds a purchase token for
+/// `token_ttl_secs`; waiting customers are told to ask again after
+/// `retry_after_secs` and lose their place if they have not asked for
+/// `queue_timeout_secs`. The defaults apply to sales created without their
+/// own limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashSalesConfig {
+    #[serde(default = "default_flash_sales_token_ttl_secs")]
+    pub token_ttl_secs: u64,
+    
+    #[serde(default = "default_flash_sales_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+    
+    #[serde(default = "default_flash_sales_retry_after_secs")]
+    pub retry_after_secs: u64,
+    
+    /// Purchase tokens held at once per sale
+    #[serde(default = "default_flash_sales_max_active_tokens")]
+    pub default_max_active_tokens: u32,
+    
+    /// Units one customer can buy per sale (0 = no limit)
+    #[serde(default = "default_flash_sales_per_customer_limit")]
+    pub default_per_customer_limit: u32,
+}
+
+impl Default for FlashSalesConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl_secs: default_flash_sales_token_ttl_secs(),
+            queue_timeout_secs: default_flash_sales_queue_timeout_secs(),
+            retry_after_secs: default_flash_sales_retry_after_secs(),
+            default_max_active_tokens: default_flash_sales_max_active_tokens(),
+            default_per_customer_limit: default_flash_sales_per_customer_limit(),
+        }
+    }
+}
+
+fn default_flash_sales_token_ttl_secs() -> u64 {
+    300
+}
+
+fn default_flash_sales_queue_timeout_secs() -> u64 {
+    30
+}
+
+fn default_flash_sales_retry_after_secs() -> u64 {
+    5
+}
+
+fn default_flash_sales_max_active_tokens() -> u32 {
+    100
+}
+
+fn default_flash_sales_per_customer_limit() -> u32 {
+    1
+}
+
+/// Idempotency key configuration
+///
+/// POST requests sent with an `Idempotency-Key` header have their response
+/// kept for `key_ttl_hours`, and retries within that time get it replayed.
+/// A request that has not finished after `lock_timeout_secs` is taken to
+/// have died and can be retried. Request and response bodies over
+/// `max_body_bytes` are not accepted with a key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    #[serde(default = "default_idempotency_key_ttl_hours")]
+    pub key_ttl_hours: u32,
+    
+    #[serde(default = "default_idempotency_lock_timeout_secs")]
+    pub lock_timeout_secs: u64,
+    
+    #[serde(default = "default_idempotency_max_body_bytes")]
+    pub max_body_bytes: usize,
+    
+    #[serde(default = "default_idempotency_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            key_ttl_hours: default_idempotency_key_ttl_hours(),
+            lock_timeout_secs: default_idempotency_lock_timeout_secs(),
+            max_body_bytes: default_idempotency_max_body_bytes(),
+            purge_interval_secs: default_idempotency_purge_interval_secs(),
+        }
+    }
+}
+
+fn default_idempotency_key_ttl_hours() -> u32 {
+    24
+}
+
+fn default_idempotency_lock_timeout_secs() -> u64 {
+    60
+}
+
+fn default_idempotency_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_idempotency_purge_interval_secs() -> u64 {
+    3600
+}
+
+/// Where the secrets master key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretsProvider {
+    /// Environment variables (`master_key_env`, `previous_master_keys_env`)
+    #[default]
+    Env,
+    /// Files (`master_key_file`, `previous_master_key_files`), e.g. mounted
+    /// by a secrets manager
+    File,
+}
+
+/// Encryption of secrets stored in the database
+///
+/// Webhook signing secrets are envelope-encrypted: each value gets its own
+/// data key, wrapped with the master key. Without a master key secrets are
+/// stored as before, in plain text. To rotate, make the new key the master
+/// key, move the old one to the previous keys and run
+/// `rcommerce secrets rotate` (or restart the server with
+/// `reencrypt_on_startup`); the old key can be dropped afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    #[serde(default)]
+    pub provider: SecretsProvider,
+    
+    /// Variable holding the base64 master key
+    #[serde(default = "default_master_key_env")]
+    pub master_key_env: String,
+    
+    /// Variable holding earlier master keys (comma-separated), still accepted for reading
+    #[serde(default = "default_previous_master_keys_env")]
+    pub previous_master_keys_env: String,
+    
+    #[serde(default)]
+    pub master_key_file: Option<PathBuf>,
+    
+    #[serde(default)]
+    pub previous_master_key_files: Vec<PathBuf>,
+    
+    /// Refuse to start without a master key
+    #[serde(default)]
+    pub require_master_key: bool,
+    
+    /// Encrypt plain-text secrets and rewrap ones under previous keys when the server starts
+    #[serde(default = "default_true")]
+    pub reencrypt_on_startup: bool,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            provider: SecretsProvider::Env,
+            master_key_env: default_master_key_env(),
+            previous_master_keys_env: default_previous_master_keys_env(),
+            master_key_file: None,
+            previous_master_key_files: Vec::new(),
+            require_master_key: false,
+            reencrypt_on_startup: true,
+        }
+    }
+}
+
+fn default_master_key_env() -> String {
+    "RCOMMERCE_MASTER_KEY".to_string()
+}
+
+fn default_previous_master_keys_env() -> String {
+    "RCOMMERCE_PREVIOUS_MASTER_KEYS".to_string()
+}
+
+/// Cookie sessions for storefronts
+///
+/// An alternative to bearer JWTs for browser storefronts: `POST
+/// /api/v1/auth/session` sets an httpOnly session cookie backed by a
+/// server-side session in Redis, plus a readable CSRF cookie whose value
+/// must be echoed in the CSRF header on every unsafe request. Bearer
+/// tokens keep working alongside, and admin routes only accept them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    #[serde(default = "default_session_cookie_name")]
+    pub cookie_name: String,
+    
+    #[serde(default = "default_csrf_cookie_name")]
+    pub csrf_cookie_name: String,
+    
+    /// Header carrying the CSRF token on POST, PUT, PATCH and DELETE
+    #[serde(default = "default_csrf_header")]
+    pub csrf_header: String,
+    
+    /// Idle timeout; each request extends the session by this much
+    #[serde(default = "default_session_ttl_secs")]
+    pub ttl_secs: u64,
+    
+    /// Sessions end this long after login regardless of activity
+    #[serde(default = "default_session_max_age_secs")]
+    pub max_age_secs: u64,
+    
+    /// Only send the cookies over HTTPS
+    #[serde(default = "default_true")]
+    pub secure: bool,
+    
+    #[serde(default)]
+    pub same_site: SameSite,
+    
+    /// Cookie domain, to share the session with subdomains
+    #[serde(default)]
+    pub domain: Option<String>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cookie_name: default_session_cookie_name(),
+            csrf_cookie_name: default_csrf_cookie_name(),
+            csrf_header: default_csrf_header(),
+            ttl_secs: default_session_ttl_secs(),
+            max_age_secs: default_session_max_age_secs(),
+            secure: true,
+            same_site: SameSite::default(),
+            domain: None,
+        }
+    }
+}
+
+/// SameSite attribute of the session cookies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+fn default_session_cookie_name() -> String {
+    "rcommerce_session".to_string()
+}
+
+fn default_csrf_cookie_name() -> String {
+    "rcommerce_csrf".to_string()
+}
+
+fn default_csrf_header() -> String {
+    "X-CSRF-Token".to_string()
+}
+
+fn default_session_ttl_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_session_max_age_secs() -> u64 {
+    30 * 24 * 3600
+}
+
+/// Recurring job scheduler
+///
+/// Recurring jobs (subscription billing and dunning retries, ...) run on
+/// cron schedules kept in the `scheduled_jobs` table. Every instance with
+/// the scheduler enabled polls for due jobs; taking a job's lease elects
+/// one instance to run it, so a job never runs twice at once. Disable the
+/// scheduler on instances that should only serve requests, or turn off
+/// `in_api_server` and run the jobs in `rcommerce worker` processes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSchedulerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Seconds between checks for due jobs
+    #[serde(default = "default_scheduler_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    
+    /// How long a run holds its job; another instance may take over a job
+    /// whose lease has run out, so keep it above the longest run
+    #[serde(default = "default_scheduler_lease_secs")]
+    pub lease_secs: u64,
+    
+    /// Name recorded on leases; defaults to the host name and process id
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    
+    /// Jobs run at once on this instance; due jobs past this wait for a
+    /// later poll (or another instance)
+    #[serde(default = "default_scheduler_concurrency")]
+    pub concurrency: usize,
+    
+    /// Run recurring jobs and background tasks (order archiving, partition
+    /// maintenance, gift card expiry, purges) in `rcommerce server`; turn
+    /// off when `rcommerce worker` processes run them
+    #[serde(default = "default_true")]
+    pub in_api_server: bool,
+    
+    /// How long a stopping worker waits for running jobs to finish
+    #[serde(default = "default_scheduler_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    
+    /// Locks electing the one instance that runs each background task
+    #[serde(default)]
+    pub locks: LockBackend,
+    
+    /// Redis locks expire this long after their holder stops refreshing
+    /// them; holders re-check their locks every third of it
+    #[serde(default = "default_scheduler_lock_ttl_secs")]
+    pub lock_ttl_secs: u64,
+    
+    /// Retries of a failed recurring job before it goes to the dead-letter queue
+    #[serde(default)]
+    pub retry: JobRetryPolicy,
+    
+    /// Retry policies of particular jobs, by job name
+    #[serde(default)]
+    pub retry_policies: std::collections::HashMap<String, JobRetryPolicy>,
+}
+
+impl JobSchedulerConfig {
+    /// Retry policy of a job
+    pub fn retry_policy(&self, job: &str) -> &JobRetryPolicy {
+        self.retry_policies.get(job).unwrap_or(&self.retry)
+    }
+}
+
+/// Exponential backoff retries of a failed recurring job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRetryPolicy {
+    /// Runs, the first included, before the job is dead-lettered; 1 turns retries off
+    #[serde(default = "default_job_retry_max_attempts")]
+    pub max_attempts: u32,
+    
+    /// Wait before the first retry
+    #[serde(default = "default_job_retry_initial_delay_secs")]
+    pub initial_delay_secs: u64,
+    
+    /// Longest wait between retries
+    #[serde(default = "default_job_retry_max_delay_secs")]
+    pub max_delay_secs: u64,
+    
+    /// Each retry waits this many times longer than the one before
+    #[serde(default = "default_job_retry_multiplier")]
+    pub multiplier: f64,
+}
+
+impl JobRetryPolicy {
+    /// Wait before retrying after `failures` failed runs in a row; None once
+    /// the job is out of attempts
+    pub fn retry_delay(&self, failures: u32) -> Option<std::time::Duration> {
+        if failures >= self.max_attempts {
+            return None;
+        }
+        crate::jobs::ExponentialBackoff::new(
+            std::time::Duration::from_secs(self.initial_delay_secs),
+            std::time::Duration::from_secs(self.max_delay_secs),
+            self.multiplier,
+        )
+        .with_jitter(0.0)
+        .calculate_delay(failures)
+    }
+    
+    fn validate(&self, section: &str) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.max_attempts == 0 {
+            return Err(Error::Config(format!("{}.max_attempts must be at least 1", section)));
+        }
+        if self.initial_delay_secs == 0 || self.max_delay_secs < self.initial_delay_secs {
+            return Err(Error::Config(format!(
+                "{}.initial_delay_secs must be at least 1 and at most max_delay_secs",
+                section
+            )));
+        }
+        if self.multiplier.is_nan() || self.multiplier < 1.0 {
+            return Err(Error::Config(format!("{}.multiplier must be at least 1", section)));
+        }
+        Ok(())
+    }
+}
+
+impl Default for JobRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_job_retry_max_attempts(),
+            initial_delay_secs: default_job_retry_initial_delay_secs(),
+            max_delay_secs: default_job_retry_max_delay_secs(),
+            multiplier: default_job_retry_multiplier(),
+        }
+    }
+}
+
+fn default_job_retry_max_attempts() -> u32 {
+    4
+}
+
+fn default_job_retry_initial_delay_secs() -> u64 {
+    60
+}
+
+fn default_job_retry_max_delay_secs() -> u64 {
+    3600
+}
+
+fn default_job_retry_multiplier() -> f64 {
+    2.0
+}
+
+/// Backend of distributed locks (see `crate::jobs::lock`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockBackend {
+    /// Session advisory locks, released when the holder's connection drops
+    #[default]
+    Postgres,
+    /// Keys set with NX and a TTL; falls back to Postgres without Redis
+    Redis,
+}
+
+impl Default for JobSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: default_scheduler_poll_interval_secs(),
+            lease_secs: default_scheduler_lease_secs(),
+            instance_id: None,
+            concurrency: default_scheduler_concurrency(),
+            in_api_server: true,
+            drain_timeout_secs: default_scheduler_drain_timeout_secs(),
+            locks: LockBackend::default(),
+            lock_ttl_secs: default_scheduler_lock_ttl_secs(),
+            retry: JobRetryPolicy::default(),
+            retry_policies: std::collections::HashMap::new(),
+        }
+    }
+}
+
+fn default_scheduler_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_scheduler_concurrency() -> usize {
+    4
+}
+
+fn default_scheduler_drain_timeout_secs() -> u64 {
+    120
+}
+
+fn default_scheduler_lock_ttl_secs() -> u64 {
+    60
+}
+
+fn default_scheduler_lease_secs() -> u64 {
+    1800
+}
+
+/// Automation rules
+///
+/// Rules (`/api/v1/admin/automation/rules`) tag or hold orders and notify
+/// Slack when order, payment and customer events match their conditions.
+/// Events are queued by database triggers and evaluated by the
+/// `automation` recurring job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationConfig {
+    /// Seconds between runs of the `automation` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_automation_run_interval_secs")]
+    pub run_interval_secs: u64,
+    
+    /// Events evaluated per run
+    #[serde(default = "default_automation_batch_size")]
+    pub batch_size: u32,
+    
+    /// Slack incoming webhook for `notify_slack` actions without their own
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    
+    /// Slack request timeout in seconds
+    #[serde(default = "default_automation_timeout_secs")]
+    pub timeout_secs: u64,
+    
+    /// Days processed events are kept (run logs are kept with their rule)
+    #[serde(default = "default_automation_event_retention_days")]
+    pub event_retention_days: u32,
+}
+
+impl Default for AutomationConfig {
+    fn default() -> Self {
+        Self {
+            run_interval_secs: default_automation_run_interval_secs(),
+            batch_size: default_automation_batch_size(),
+            slack_webhook_url: None,
+            timeout_secs: default_automation_timeout_secs(),
+            event_retention_days: default_automation_event_retention_days(),
+        }
+    }
+}
+
+fn default_automation_run_interval_secs() -> u64 {
+    60
+}
+
+fn default_automation_batch_size() -> u32 {
+    200
+}
+
+fn default_automation_timeout_secs() -> u64 {
+    10
+}
+
+fn default_automation_event_retention_days() -> u32 {
+    7
+}
+
+/// Scheduled reports
+///
+/// Report subscriptions (`/api/v1/admin/reports/subscriptions`) email
+/// sales, tax, low stock, failed payment and checkout funnel reports to
+/// stakeholders on a daily, weekly or monthly schedule. The `reports` recurring job generates
+/// due reports; the emails link to the file under `public_url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportsConfig {
+    /// Seconds between runs of the `reports` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_reports_run_interval_secs")]
+    pub run_interval_secs: u64,
+    
+    /// Base URL of this API for download links in report emails
+    #[serde(default = "default_reports_public_url")]
+    pub public_url: String,
+    
+    /// Days report files are kept and their download links work
+    #[serde(default = "default_reports_retention_days")]
+    pub retention_days: u32,
+    
+    /// Rows included in a report; the file notes when more matched
+    #[serde(default = "default_reports_max_rows")]
+    pub max_rows: u32,
+    
+    /// Subscriptions run per job run
+    #[serde(default = "default_reports_batch_size")]
+    pub batch_size: u32,
+    
+    /// Seconds between refreshes of the sales rollups (`sales_views_refresh`
+    /// job), when it is first registered; sales reports lag by up to this
+    #[serde(default = "default_reports_sales_refresh_interval_secs")]
+    pub sales_refresh_interval_secs: u64,
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self {
+            run_interval_secs: default_reports_run_interval_secs(),
+            public_url: default_reports_public_url(),
+            retention_days: default_reports_retention_days(),
+            max_rows: default_reports_max_rows(),
+            batch_size: default_reports_batch_size(),
+            sales_refresh_interval_secs: default_reports_sales_refresh_interval_secs(),
+        }
+    }
+}
+
+fn default_reports_run_interval_secs() -> u64 {
+    300
+}
+
+fn default_reports_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_reports_retention_days() -> u32 {
+    30
+}
+
+fn default_reports_max_rows() -> u32 {
+    10_000
+}
+
+fn default_reports_batch_size() -> u32 {
+    20
+}
+
+fn default_reports_sales_refresh_interval_secs() -> u64 {
+    900
+}
+
+/// URL redirects
+///
+/// When a product or category slug changes its old slug is kept, so the
+/// storefront URLs below resolve to the current one with a 301
+/// (`/api/v1/redirects/resolve?path=`). Manual redirects for other paths
+/// are managed under `/api/v1/admin/redirects`; each instance keeps them in
+/// memory and reloads them every `cache_ttl_secs` (or as soon as they change
+/// through its own API).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectsConfig {
+    /// Storefront product URL path, with `{slug}` for the product's slug
+    #[serde(default = "default_redirects_product_path")]
+    pub product_path: String,
+    
+    /// Storefront category URL path, with `{slug}` for the category's slug
+    #[serde(default = "default_redirects_category_path")]
+    pub category_path: String,
+    
+    /// Seconds manual redirects are served from memory before reloading;
+    /// counted hits are written back on reload
+    #[serde(default = "default_redirects_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for RedirectsConfig {
+    fn default() -> Self {
+        Self {
+            product_path: default_redirects_product_path(),
+            category_path: default_redirects_category_path(),
+            cache_ttl_secs: default_redirects_cache_ttl_secs(),
+        }
+    }
+}
+
+fn default_redirects_product_path() -> String {
+    "/products/{slug}".to_string()
+}
+
+fn default_redirects_category_path() -> String {
+    "/categories/{slug}".to_string()
+}
+
+fn default_redirects_cache_ttl_secs() -> u64 {
+    60
+}
+
+/// First-party storefront analytics
+///
+/// Storefronts post funnel events (product viewed, added to cart, checkout
+/// started, payment submitted) in batches to `/api/v1/storefront/events`;
+/// `/api/v1/admin/analytics/funnel` and the `checkout_funnel` report count
+/// the sessions reaching each step. Session IDs are stored as salted hashes
+/// and no IP address or user agent is kept. Requests with `DNT: 1` or
+/// `Sec-GPC: 1` are dropped when `honor_do_not_track` is set. Sessions are
+/// sampled by `sample_rate`; funnel counts are scaled back up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Share of sessions whose events are kept (0 to 1)
+    #[serde(default = "default_analytics_sample_rate")]
+    pub sample_rate: f64,
+    
+    /// Events accepted per request
+    #[serde(default = "default_analytics_max_batch_size")]
+    pub max_batch_size: usize,
+    
+    /// Drop events from browsers sending `DNT: 1` or `Sec-GPC: 1`
+    #[serde(default = "default_true")]
+    pub honor_do_not_track: bool,
+    
+    /// Salt of the session ID hashes; changing it starts new sessions
+    #[serde(default)]
+    pub session_salt: String,
+    
+    /// Days events are kept
+    #[serde(default = "default_analytics_retention_days")]
+    pub retention_days: u32,
+    
+    /// Seconds between runs of the `analytics_retention` job, when it is
+    /// first registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_analytics_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: default_analytics_sample_rate(),
+            max_batch_size: default_analytics_max_batch_size(),
+            honor_do_not_track: true,
+            session_salt: String::new(),
+            retention_days: default_analytics_retention_days(),
+            purge_interval_secs: default_analytics_purge_interval_secs(),
+        }
+    }
+}
+
+impl AnalyticsConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if !(self.sample_rate > 0.0 && self.sample_rate <= 1.0) {
+            return Err(Error::Config("analytics.sample_rate must be above 0 and at most 1".to_string()));
+        }
+        if !(1..=500).contains(&self.max_batch_size) {
+            return Err(Error::Config("analytics.max_batch_size must be between 1 and 500".to_string()));
+        }
+        if self.retention_days == 0 {
+            return Err(Error::Config("analytics.retention_days must be at least 1".to_string()));
+        }
+        if self.purge_interval_secs < 60 {
+            return Err(Error::Config("analytics.purge_interval_secs must be at least 60".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_analytics_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_analytics_max_batch_size() -> usize {
+    50
+}
+
+fn default_analytics_retention_days() -> u32 {
+    180
+}
+
+fn default_analytics_purge_interval_secs() -> u64 {
+    86400
+}
+
+/// Embedded admin UI
+///
+/// A small web UI compiled into the API binary for browsing orders, issuing
+/// refunds, adjusting stock and managing API keys. The page itself holds no
+/// data: staff sign in with their account and every call it makes goes to
+/// the admin API with their token, so the usual staff permissions apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUiConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Path the UI is served at
+    #[serde(default = "default_admin_ui_path")]
+    pub path: String,
+}
+
+impl Default for AdminUiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: default_admin_ui_path(),
+        }
+    }
+}
+
+impl AdminUiConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if !self.enabled {
+            return Ok(());
+        }
+        let path = self.path.as_str();
+        if !path.starts_with('/') || path.len() < 2 || path.ends_with('/') {
+            return Err(Error::Config("admin_ui.path must start with / and not end with one, e.g. /admin".to_string()));
+        }
+        if path.contains(['*', ':', '?', '#']) {
+            return Err(Error::Config("admin_ui.path must be a plain path".to_string()));
+        }
+        let reserved = ["/api", "/health", "/ready", "/metrics", "/uploads", "/.well-known"];
+        if reserved.iter().any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix))) {
+            return Err(Error::Config(format!("admin_ui.path {} clashes with an API route", path)));
+        }
+        Ok(())
+    }
+}
+
+fn default_admin_ui_path() -> String {
+    "/admin".to_string()
+}
+
+/// Business metrics and alerts
+///
+/// `GET /metrics` (with `[features] metrics`) serves orders per minute, the
+/// payment failure rate and checkout funnel counts over the last
+/// `metrics_window_secs` in the Prometheus text format. Alert rules check
+/// the same metrics from the `metric_alerts` recurring job and email staff
+/// and/or post to Slack when a threshold is breached, and again when it
+/// recovers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservabilityConfig {
+    /// Bearer token `/metrics` requires; without one it is open to anyone
+    /// who can reach the server
+    #[serde(default)]
+    pub metrics_token: Option<String>,
+    
+    /// Seconds of orders, payments and carts the metrics on `/metrics` cover
+    #[serde(default = "default_metrics_window_secs")]
+    pub metrics_window_secs: u64,
+    
+    #[serde(default)]
+    pub alerts: MetricAlertsConfig,
+    
+    #[serde(default)]
+    pub anomalies: AnomalyDetectionConfig,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            metrics_token: None,
+            metrics_window_secs: default_metrics_window_secs(),
+            alerts: MetricAlertsConfig::default(),
+            anomalies: AnomalyDetectionConfig::default(),
+        }
+    }
+}
+
+impl ObservabilityConfig {
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        if self.metrics_token.as_deref().is_some_and(|token| token.len() < 16) {
+            return Err(Error::Config("observability.metrics_token must be at least 16 characters".to_string()));
+        }
+        if self.metrics_window_secs < 60 {
+            return Err(Error::Config("observability.metrics_window_secs must be at least 60".to_string()));
+        }
+        
+        let alerts = &self.alerts;
+        if alerts.check_interval_secs < 60 {
+            return Err(Error::Config("observability.alerts.check_interval_secs must be at least 60".to_string()));
+        }
+        if alerts.alert_roles.contains(&crate::models::CustomerRole::Customer) {
+            return Err(Error::Config("observability.alerts.alert_roles must be staff roles".to_string()));
+        }
+        if let Some(ref url) = alerts.slack_webhook_url {
+            if !url.starts_with("https://") {
+                return Err(Error::Config("observability.alerts.slack_webhook_url must be an https URL".to_string()));
+            }
+        }
+        if !alerts.rules.is_empty() && alerts.alert_roles.is_empty() && alerts.slack_webhook_url.is_none() {
+            return Err(Error::Config(
+                "observability.alerts needs alert_roles or a slack_webhook_url to notify".to_string(),
+            ));
+        }
+        
+        let mut names = std::collections::HashSet::new();
+        for rule in &alerts.rules {
+            let name = &rule.name;
+            if name.is_empty() || name.len() > 100 {
+                return Err(Error::Config("observability.alerts.rules need a name of 1 to 100 characters".to_string()));
+            }
+            if !names.insert(name.as_str()) {
+                return Err(Error::Config(format!("observability.alerts.rules has more than one rule named {}", name)));
+            }
+            let threshold = match (rule.above, rule.below) {
+                (Some(threshold), None) | (None, Some(threshold)) => threshold,
+                _ => {
+                    return Err(Error::Config(format!(
+                        "observability.alerts rule {} needs exactly one of above and below",
+                        name
+                    )))
+                }
+            };
+            if !threshold.is_finite() || threshold < 0.0 || (rule.metric.is_percentage() && threshold > 100.0) {
+                return Err(Error::Config(format!(
+                    "observability.alerts rule {} has an out of range threshold ({} is {})",
+                    name,
+                    rule.metric.as_str(),
+                    rule.metric.unit()
+                )));
+            }
+            if rule.window_secs < 60 {
+                return Err(Error::Config(format!(
+                    "observability.alerts rule {} needs a window_secs of at least 60",
+                    name
+                )));
+            }
+        }
+        
+        let anomalies = &self.anomalies;
+        if anomalies.enabled {
+            if anomalies.check_interval_secs < 60 || anomalies.bucket_secs < 60 {
+                return Err(Error::Config(
+                    "observability.anomalies.check_interval_secs and bucket_secs must be at least 60".to_string(),
+                ));
+            }
+            if !(8..=2016).contains(&anomalies.baseline_buckets) {
+                return Err(Error::Config("observability.anomalies.baseline_buckets must be between 8 and 2016".to_string()));
+            }
+            if !(anomalies.z_threshold.is_finite() && anomalies.z_threshold > 0.0) {
+                return Err(Error::Config("observability.anomalies.z_threshold must be positive".to_string()));
+            }
+            if !(0.0..=100.0).contains(&anomalies.min_rate) {
+                return Err(Error::Config("observability.anomalies.min_rate must be a percentage".to_string()));
+            }
+            if alerts.alert_roles.is_empty() && alerts.slack_webhook_url.is_none() {
+                return Err(Error::Config(
+                    "observability.anomalies needs observability.alerts.alert_roles or a slack_webhook_url to notify".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_metrics_window_secs() -> u64 {
+    300
+}
+
+/// Alert rules on business metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricAlertsConfig {
+    /// Seconds between runs of the `metric_alerts` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_alerts_check_interval_secs")]
+    pub check_interval_secs: u64,
+    
+    /// Staff roles emailed when an alert fires or resolves (empty for none)
+    #[serde(default = "default_alert_roles")]
+    pub alert_roles: Vec<crate::models::CustomerRole>,
+    
+    /// Slack incoming webhook alerts are posted to
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+    
+    /// Slack request timeout in seconds
+    #[serde(default = "default_alerts_timeout_secs")]
+    pub timeout_secs: u64,
+    
+    /// Seconds before staff are reminded of an alert that is still firing
+    /// (0 to notify once per breach)
+    #[serde(default = "default_alerts_repeat_after_secs")]
+    pub repeat_after_secs: u64,
+    
+    #[serde(default)]
+    pub rules: Vec<MetricAlertRule>,
+}
+
+impl Default for MetricAlertsConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: default_alerts_check_interval_secs(),
+            alert_roles: default_alert_roles(),
+            slack_webhook_url: None,
+            timeout_secs: default_alerts_timeout_secs(),
+            repeat_after_secs: default_alerts_repeat_after_secs(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// An alert on a business metric, e.g. a payment failure rate above 10%
+/// for 5 minutes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricAlertRule {
+    /// Names the alert in notifications and in `/metrics`
+    pub name: String,
+    
+    pub metric: crate::observability::BusinessMetric,
+    
+    /// Fire when the metric is above this (rates are percentages)
+    #[serde(default)]
+    pub above: Option<f64>,
+    
+    /// Fire when the metric is below this (rates are percentages)
+    #[serde(default)]
+    pub below: Option<f64>,
+    
+    /// Seconds of orders, payments or carts the metric is measured over
+    #[serde(default = "default_alert_window_secs")]
+    pub window_secs: u64,
+    
+    /// Seconds the threshold must stay breached before the alert fires
+    #[serde(default)]
+    pub for_secs: u64,
+    
+    /// Payments (failure rate) or carts (conversion rate) a window needs
+    /// before a rate is checked, so a handful of events cannot fire it
+    #[serde(default = "default_alert_min_samples")]
+    pub min_samples: i64,
+}
+
+/// Anomaly detection on payment failure, refund and 5xx rates
+///
+/// The `anomaly_detection` job measures each rate over the latest
+/// `bucket_secs` and compares it with the `baseline_buckets` before it: a
+/// rate `z_threshold` standard deviations above their mean (and at least
+/// `min_rate`) opens an incident and notifies the staff and Slack channel
+/// of `[observability.alerts]`. The incident resolves once the rate is back
+/// to normal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyDetectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Seconds between runs of the `anomaly_detection` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_anomaly_check_interval_secs")]
+    pub check_interval_secs: u64,
+    
+    /// Seconds each rate is measured over
+    #[serde(default = "default_anomaly_bucket_secs")]
+    pub bucket_secs: u64,
+    
+    /// Windows before the latest one that make up the baseline
+    #[serde(default = "default_anomaly_baseline_buckets")]
+    pub baseline_buckets: u32,
+    
+    /// Standard deviations above the baseline mean that count as an anomaly
+    #[serde(default = "default_anomaly_z_threshold")]
+    pub z_threshold: f64,
+    
+    /// Events (payments, orders or requests) a window needs to be measured
+    #[serde(default = "default_anomaly_min_samples")]
+    pub min_samples: i64,
+    
+    /// Smallest rate (a percentage) that can be an anomaly, so a spike from
+    /// 0.1% to 0.5% does not open an incident
+    #[serde(default = "default_anomaly_min_rate")]
+    pub min_rate: f64,
+    
+    /// Rates watched
+    #[serde(default = "crate::models::AnomalyMetric::all")]
+    pub metrics: Vec<crate::models::AnomalyMetric>,
+}
+
+impl Default for AnomalyDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_secs: default_anomaly_check_interval_secs(),
+            bucket_secs: default_anomaly_bucket_secs(),
+            baseline_buckets: default_anomaly_baseline_buckets(),
+            z_threshold: default_anomaly_z_threshold(),
+            min_samples: default_anomaly_min_samples(),
+            min_rate: default_anomaly_min_rate(),
+            metrics: crate::models::AnomalyMetric::all(),
+        }
+    }
+}
+
+fn default_anomaly_check_interval_secs() -> u64 {
+    300
+}
+
+fn default_anomaly_bucket_secs() -> u64 {
+    900
+}
+
+fn default_anomaly_baseline_buckets() -> u32 {
+    96
+}
+
+fn default_anomaly_z_threshold() -> f64 {
+    3.0
+}
+
+fn default_anomaly_min_samples() -> i64 {
+    20
+}
+
+fn default_anomaly_min_rate() -> f64 {
+    2.0
+}
+
+fn default_alerts_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_alert_roles() -> Vec<crate::models::CustomerRole> {
+    vec![crate::models::CustomerRole::Admin]
+}
+
+fn default_alerts_timeout_secs() -> u64 {
+    10
+}
+
+fn default_alerts_repeat_after_secs() -> u64 {
+    3600
+}
+
+fn default_alert_window_secs() -> u64 {
+    300
+}
+
+fn default_alert_min_samples() -> i64 {
+    10
+}
+
+/// Marketplace sync (Amazon, eBay)
+///
+/// Products and variants are listed on a marketplace by mapping marketplace
+/// SKUs to them (`/api/v1/admin/marketplaces/listings`). The
+/// `marketplace_sync` recurring job pushes the price and stock of listings
+/// that changed since their last push, and imports new marketplace orders
+/// with their channel. Configure a section per marketplace to sync it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceConfig {
+    /// Seconds between sync runs; sets the `marketplace_sync` job's
+    /// schedule when it is first registered (change it later with
+    /// `rcommerce jobs schedule`)
+    #[serde(default = "default_marketplace_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+    
+    /// How far back the first order import of a marketplace looks, in hours
+    #[serde(default = "default_marketplace_order_lookback_hours")]
+    pub order_lookback_hours: u32,
+    
+    /// Listings pushed per marketplace and run
+    #[serde(default = "default_marketplace_batch_size")]
+    pub batch_size: u32,
+    
+    /// Marketplace API request timeout in seconds
+    #[serde(default = "default_marketplace_timeout_secs")]
+    pub timeout_secs: u64,
+    
+    #[serde(default)]
+    pub amazon: Option<AmazonMarketplaceConfig>,
+    
+    #[serde(default)]
+    pub ebay: Option<EbayMarketplaceConfig>,
+}
+
+impl MarketplaceConfig {
+    /// Whether any marketplace is configured
+    pub fn any_configured(&self) -> bool {
+        self.amazon.is_some() || self.ebay.is_some()
+    }
+}
+
+impl Default for MarketplaceConfig {
+    fn default() -> Self {
+        Self {
+            sync_interval_secs: default_marketplace_sync_interval_secs(),
+            order_lookback_hours: default_marketplace_order_lookback_hours(),
+            batch_size: default_marketplace_batch_size(),
+            timeout_secs: default_marketplace_timeout_secs(),
+            amazon: None,
+            ebay: None,
+        }
+    }
+}
+
+fn default_marketplace_sync_interval_secs() -> u64 {
+    300
+}
+
+fn default_marketplace_order_lookback_hours() -> u32 {
+    72
+}
+
+fn default_marketplace_batch_size() -> u32 {
+    100
+}
+
+fn default_marketplace_timeout_secs() -> u64 {
+    30
+}
+
+/// Amazon Selling Partner API settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmazonMarketplaceConfig {
+    /// Merchant token of the seller account
+    pub seller_id: String,
+    /// Marketplace listings and orders belong to, e.g. ATVPDKIKX0DER (US)
+    pub marketplace_id: String,
+    /// Login with Amazon app credentials
+    pub lwa_client_id: String,
+    pub lwa_client_secret: String,
+    /// Refresh token from authorizing the app for the seller
+    pub refresh_token: String,
+    /// Regional SP-API endpoint
+    #[serde(default = "default_amazon_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "default_amazon_token_url")]
+    pub token_url: String,
+    /// Product type sent with listing patches
+    #[serde(default = "default_amazon_product_type")]
+    pub product_type: String,
+}
+
+fn default_amazon_endpoint() -> String {
+    "https://sellingpartnerapi-na.amazon.com".to_string()
+}
+
+fn default_amazon_token_url() -> String {
+    "https://api.amazon.com/auth/o2/token".to_string()
+}
+
+fn default_amazon_product_type() -> String {
+    "PRODUCT".to_string()
+}
+
+/// eBay Sell API settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EbayMarketplaceConfig {
+    /// Application keys
+    pub client_id: String,
+    pub client_secret: String,
+    /// User refresh token with the sell.inventory and sell.fulfillment scopes
+    pub refresh_token: String,
+    #[serde(default = "default_ebay_marketplace_id")]
+    pub marketplace_id: String,
+    /// https://api.sandbox.ebay.com for the sandbox
+    #[serde(default = "default_ebay_api_url")]
+    pub api_url: String,
+}
+
+fn default_ebay_marketplace_id() -> String {
+    "EBAY_US".to_string()
+}
+
+fn default_ebay_api_url() -> String {
+    "https://api.ebay.com".to_string()
 }
 
 /// Import configuration for platform API keys and settings
@@ -861,6 +4856,10 @@ pub struct TlsConfig {
     /// HTTPS port (default: 443)
     #[serde(default = "default_https_port")]
     pub https_port: u16,
+
+    /// Mutual TLS: verify client certificates against a CA (manual certs only)
+    #[serde(default)]
+    pub client_auth: Option<ClientAuthConfig>,
 }
 
 impl Default for TlsConfig {
@@ -877,6 +4876,7 @@ impl Default for TlsConfig {
             ocsp_stapling: true,
             http_port: 80,
             https_port: 443,
+            client_auth: None,
         }
     }
 }
@@ -899,6 +4899,17 @@ impl TlsConfig {
             if self.min_tls_version < TlsVersion::Tls1_2 {
                 return Err("Minimum TLS version must be 1.2 or higher".to_string());
             }
+
+            if let Some(client_auth) = self.client_auth.as_ref().filter(|c| c.enabled) {
+                if !has_manual_certs || has_lets_encrypt {
+                    return Err(
+                        "Client certificate authentication requires manual certificate files".to_string(),
+                    );
+                }
+                if client_auth.ca_file.as_os_str().is_empty() {
+                    return Err("tls.client_auth.ca_file is required".to_string());
+                }
+            }
         }
 
         Ok(())
@@ -913,6 +4924,18 @@ impl TlsConfig {
     pub fn uses_manual_certs(&self) -> bool {
         self.enabled && self.cert_file.is_some() && self.key_file.is_some()
     }
+
+    /// Client certificate authentication, if it is in effect
+    pub fn client_auth(&self) -> Option<&ClientAuthConfig> {
+        self.client_auth
+            .as_ref()
+            .filter(|c| c.enabled && self.uses_manual_certs() && !self.uses_lets_encrypt())
+    }
+
+    /// Check if admin routes require a verified client certificate
+    pub fn admin_requires_client_cert(&self) -> bool {
+        self.client_auth().is_some_and(|c| c.require_for_admin)
+    }
 }
 
 /// TLS version enum
@@ -1078,6 +5101,25 @@ fn default_hsts_max_age() -> u64 {
     31_536_000 // 1 year
 }
 
+/// Mutual TLS configuration
+///
+/// Clients may present a certificate signed by `ca_file`; connections
+/// without one are still accepted so the storefront keeps working, but
+/// admin routes refuse them when `require_for_admin` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAuthConfig {
+    /// Enable client certificate verification
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// PEM file with the CA certificate(s) client certificates must chain to
+    pub ca_file: PathBuf,
+
+    /// Refuse admin requests without a verified client certificate
+    #[serde(default = "default_true")]
+    pub require_for_admin: bool,
+}
+
 /// Dunning (payment retry) configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DunningConfig {
@@ -1144,6 +5186,20 @@ impl Default for DunningConfig {
     }
 }
 
+impl From<&DunningConfig> for crate::models::DunningConfig {
+    fn from(config: &DunningConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            retry_intervals_days: config.retry_intervals_days.clone(),
+            grace_period_days: config.grace_period_days,
+            email_on_first_failure: config.email_on_first_failure,
+            email_on_final_failure: config.email_on_final_failure,
+            late_fee_after_retry: config.late_fee_after_retry,
+            late_fee_amount: config.late_fee_amount,
+        }
+    }
+}
+
 /// Per-gateway dunning configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GatewayDunningConfig {
@@ -1277,6 +5333,26 @@ pub struct PaymentConfig {
     /// Airwallex configuration
     #[serde(default)]
     pub airwallex: AirwallexConfig,
+    
+    /// Hosted checkout pages (Stripe Checkout and Payment Links)
+    #[serde(default)]
+    pub hosted_checkout: HostedCheckoutConfig,
+    
+    /// Wallet payments (Apple Pay, Google Pay)
+    #[serde(default)]
+    pub wallets: WalletConfig,
+    
+    /// 3-D Secure challenges of card payments
+    #[serde(default)]
+    pub three_d_secure: ThreeDSecureConfig,
+    
+    /// Authorize-now, capture-later payments
+    #[serde(default)]
+    pub capture: PaymentCaptureConfig,
+    
+    /// Matching gateway payouts against recorded payments
+    #[serde(default)]
+    pub reconciliation: PayoutReconciliationConfig,
 }
 
 fn default_payment_gateway() -> String {
@@ -1358,47 +5434,480 @@ pub struct AirwallexConfig {
     /// Client ID
     pub client_id: Option<String>,
     
-    /// API Key
-    pub api_key: Option<String>,
+    /// API Key
+    pub api_key: Option<String>,
+    
+    /// Webhook secret
+    pub webhook_secret: Option<String>,
+    
+    /// Use demo environment
+    #[serde(default)]
+    pub demo: bool,
+}
+
+/// Hosted checkout configuration
+/// 
+/// Redirect URLs may contain `{order_id}`. URLs given when creating a
+/// checkout must point at one of `allowed_redirect_hosts`; without any, only
+/// the URLs configured here are used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostedCheckoutConfig {
+    /// Where customers return after paying
+    #[serde(default)]
+    pub success_url: Option<String>,
+    
+    /// Where customers return when they cancel a checkout session
+    #[serde(default)]
+    pub cancel_url: Option<String>,
+    
+    /// Minutes until a checkout session expires (30-1440)
+    #[serde(default = "default_hosted_checkout_expires_after_mins")]
+    pub expires_after_mins: u32,
+    
+    /// Hosts that requested redirect URLs may point at
+    #[serde(default)]
+    pub allowed_redirect_hosts: Vec<String>,
+}
+
+impl Default for HostedCheckoutConfig {
+    fn default() -> Self {
+        Self {
+            success_url: None,
+            cancel_url: None,
+            expires_after_mins: default_hosted_checkout_expires_after_mins(),
+            allowed_redirect_hosts: Vec::new(),
+        }
+    }
+}
+
+fn default_hosted_checkout_expires_after_mins() -> u32 {
+    60
+}
+
+/// 3-D Secure (Strong Customer Authentication) configuration
+///
+/// Gateways send customers back from the card issuer's challenge to
+/// `{public_url}/api/v1/payments/sessions/:id/return`, which completes the
+/// payment and redirects to the storefront's `return_url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreeDSecureConfig {
+    /// Base URL of this API, as customers' browsers reach it
+    #[serde(default = "default_three_d_secure_public_url")]
+    pub public_url: String,
+    
+    /// Storefront page customers land on after a challenge, unless the
+    /// payment request names one; `payment_session` and `status` are added
+    /// to its query
+    #[serde(default)]
+    pub return_url: Option<String>,
+    
+    /// Hosts that requested return URLs may point at
+    #[serde(default)]
+    pub allowed_return_hosts: Vec<String>,
+    
+    /// Minutes customers have to complete a challenge (5-120)
+    #[serde(default = "default_challenge_expires_mins")]
+    pub challenge_expires_mins: u32,
+}
+
+impl Default for ThreeDSecureConfig {
+    fn default() -> Self {
+        Self {
+            public_url: default_three_d_secure_public_url(),
+            return_url: None,
+            allowed_return_hosts: Vec::new(),
+            challenge_expires_mins: default_challenge_expires_mins(),
+        }
+    }
+}
+
+fn default_three_d_secure_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_challenge_expires_mins() -> u32 {
+    30
+}
+
+/// When payments are captured
+///
+/// With `method = "manual"` payments started through the payments API are
+/// only authorized. Staff capture them (in parts if the gateway allows)
+/// through `/api/v1/admin/payments/:id/capture`, or, with `on_fulfillment`,
+/// each shipment's share is captured as it ships. Authorizations not
+/// captured in time are voided by the authorization expiry job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentCaptureConfig {
+    #[serde(default)]
+    pub method: crate::payment::agnostic::CaptureMethod,
+    
+    /// Capture as orders ship, unless an order says otherwise
+    #[serde(default)]
+    pub on_fulfillment: bool,
+    
+    /// Days an authorization is kept before it is voided (1-30); card
+    /// networks usually hold funds for 7
+    #[serde(default = "default_authorization_valid_days")]
+    pub authorization_valid_days: u32,
+    
+    /// How often the authorization expiry job runs
+    #[serde(default = "default_authorization_check_interval_secs")]
+    pub expiry_check_interval_secs: u64,
+}
+
+impl Default for PaymentCaptureConfig {
+    fn default() -> Self {
+        Self {
+            method: Default::default(),
+            on_fulfillment: false,
+            authorization_valid_days: default_authorization_valid_days(),
+            expiry_check_interval_secs: default_authorization_check_interval_secs(),
+        }
+    }
+}
+
+fn default_authorization_valid_days() -> u32 {
+    7
+}
+
+fn default_authorization_check_interval_secs() -> u64 {
+    3600
+}
+
+/// Payout reconciliation
+///
+/// The payout reconciliation job imports the payouts `gateways` made in the
+/// last `lookback_days` and matches each paid out transaction against the
+/// recorded payments and refunds. Unknown references and differing amounts
+/// are flagged in `/api/v1/admin/payments/reconciliation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutReconciliationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Gateways whose payouts are imported
+    #[serde(default = "default_reconciliation_gateways")]
+    pub gateways: Vec<String>,
+    
+    /// Days of payouts imported each run (1-90); re-imported payouts are
+    /// matched again
+    #[serde(default = "default_reconciliation_lookback_days")]
+    pub lookback_days: u32,
+    
+    /// Amount differences up to this are not flagged
+    #[serde(default)]
+    pub amount_tolerance: rust_decimal::Decimal,
+    
+    /// Payments not paid out this many days after they were taken are
+    /// reported as unsettled
+    #[serde(default = "default_settlement_days")]
+    pub settlement_days: u32,
+    
+    /// How often the reconciliation job runs
+    #[serde(default = "default_reconciliation_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for PayoutReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gateways: default_reconciliation_gateways(),
+            lookback_days: default_reconciliation_lookback_days(),
+            amount_tolerance: rust_decimal::Decimal::ZERO,
+            settlement_days: default_settlement_days(),
+            interval_secs: default_reconciliation_interval_secs(),
+        }
+    }
+}
+
+fn default_reconciliation_gateways() -> Vec<String> {
+    vec!["stripe".to_string()]
+}
+
+fn default_reconciliation_lookback_days() -> u32 {
+    14
+}
+
+fn default_settlement_days() -> u32 {
+    7
+}
+
+fn default_reconciliation_interval_secs() -> u64 {
+    21600
+}
+
+/// Wallet payment configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WalletConfig {
+    #[serde(default)]
+    pub apple_pay: ApplePayConfig,
+    
+    #[serde(default)]
+    pub google_pay: GooglePayConfig,
+}
+
+/// Apple Pay configuration
+/// 
+/// With Stripe's or Airwallex's JS SDK, registering the storefront domains
+/// with the gateway is enough. Validating merchant sessions here instead
+/// needs the Apple Pay merchant identity certificate and key (PEM files).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplePayConfig {
+    /// Offer Apple Pay where the gateway supports it
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Apple merchant ID, e.g. "merchant.com.example.shop"
+    #[serde(default)]
+    pub merchant_id: Option<String>,
+    
+    /// Name shown on the payment sheet
+    #[serde(default = "default_apple_pay_display_name")]
+    pub display_name: String,
+    
+    /// Storefront domains the payment sheet is opened on
+    #[serde(default)]
+    pub domains: Vec<String>,
+    
+    /// Merchant identity certificate (PEM file path)
+    #[serde(default)]
+    pub merchant_identity_cert: Option<String>,
+    
+    /// Merchant identity private key (PEM file path)
+    #[serde(default)]
+    pub merchant_identity_key: Option<String>,
+    
+    /// Domain verification file from Apple or the gateway, served at
+    /// /.well-known/apple-developer-merchantid-domain-association
+    #[serde(default)]
+    pub domain_association_file: Option<String>,
+}
+
+impl Default for ApplePayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            merchant_id: None,
+            display_name: default_apple_pay_display_name(),
+            domains: Vec::new(),
+            merchant_identity_cert: None,
+            merchant_identity_key: None,
+            domain_association_file: None,
+        }
+    }
+}
+
+fn default_apple_pay_display_name() -> String {
+    "R Commerce".to_string()
+}
+
+/// Google Pay configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GooglePayConfig {
+    /// Offer Google Pay where the gateway supports it
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Google Pay merchant ID (needed in production)
+    #[serde(default)]
+    pub merchant_id: Option<String>,
+    
+    /// Name shown on the payment sheet
+    #[serde(default)]
+    pub merchant_name: Option<String>,
+}
+
+impl Default for GooglePayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            merchant_id: None,
+            merchant_name: None,
+        }
+    }
+}
+
+/// Shipping configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ShippingConfig {
+    /// Default shipping provider
+    #[serde(default = "default_shipping_provider")]
+    pub default_provider: String,
+    
+    /// Enable test mode for shipping (use sandbox APIs)
+    #[serde(default)]
+    pub test_mode: bool,
+    
+    /// Default shipping origin address
+    #[serde(default)]
+    pub origin: Option<ShippingOriginConfig>,
+    
+    /// DHL Express configuration
+    #[serde(default)]
+    pub dhl: DhlConfig,
+    
+    /// FedEx configuration
+    #[serde(default)]
+    pub fedex: FedExConfig,
+    
+    /// UPS configuration
+    #[serde(default)]
+    pub ups: UpsConfig,
+    
+    /// USPS configuration
+    #[serde(default)]
+    pub usps: UspsConfig,
+    
+    /// EasyPost configuration
+    #[serde(default)]
+    pub easypost: EasyPostConfig,
+    
+    /// ShipStation configuration
+    #[serde(default)]
+    pub shipstation: ShipStationConfig,
+    
+    /// Carrier accounts and origins of inventory locations shipping
+    /// under their own accounts (e.g. warehouses in other countries)
+    #[serde(default)]
+    pub locations: Vec<LocationShippingConfig>,
+    
+    /// Automatic tracking updates
+    #[serde(default)]
+    pub tracking: TrackingConfig,
+}
+
+impl ShippingConfig {
+    /// The settings of the inventory location with `code`, if it has any
+    pub fn location(&self, code: &str) -> Option<&LocationShippingConfig> {
+        self.locations.iter().find(|location| location.location.eq_ignore_ascii_case(code))
+    }
+    
+    /// Location codes must be set and listed once
+    fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+        
+        let mut seen = std::collections::HashSet::new();
+        for location in &self.locations {
+            if location.location.trim().is_empty() {
+                return Err(Error::Config("shipping.locations need a location code".to_string()));
+            }
+            if !seen.insert(location.location.to_ascii_lowercase()) {
+                return Err(Error::Config(format!(
+                    "shipping.locations lists location {} more than once",
+                    location.location
+                )));
+            }
+        }
+        
+        let tracking = &self.tracking;
+        if tracking.enabled {
+            if tracking.poll_interval_secs == 0 {
+                return Err(Error::Config("shipping.tracking.poll_interval_secs must be positive".to_string()));
+            }
+            if tracking.poll_batch_size <= 0 {
+                return Err(Error::Config("shipping.tracking.poll_batch_size must be positive".to_string()));
+            }
+            let webhooks = self.easypost.enabled || self.shipstation.enabled;
+            if webhooks && tracking.webhook_token.len() < 16 {
+                return Err(Error::Config(
+                    "shipping.tracking.webhook_token must be at least 16 characters for EasyPost or ShipStation webhooks"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Automatic tracking updates of shipments with a bought label
+///
+/// EasyPost and ShipStation push updates to
+/// `/api/v1/shipping/tracking/<provider>?token=<webhook_token>` once
+/// registered (`POST /api/v1/admin/shipping/tracking/webhooks`). Shipments
+/// of carriers without webhooks (DHL, FedEx, UPS, USPS) are polled by the
+/// `tracking_poll` job. Customers are told when a parcel ships and when it
+/// is delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Shared secret in the webhook URLs registered with aggregators
+    #[serde(default)]
+    pub webhook_token: String,
+    
+    /// Base URL of this API the aggregators can reach
+    #[serde(default = "default_tracking_public_url")]
+    pub public_url: String,
     
-    /// Webhook secret
-    pub webhook_secret: Option<String>,
+    /// Seconds between runs of the `tracking_poll` job, when it is first
+    /// registered (change it later with `rcommerce jobs schedule`)
+    #[serde(default = "default_tracking_poll_interval_secs")]
+    pub poll_interval_secs: u64,
     
-    /// Use demo environment
-    #[serde(default)]
-    pub demo: bool,
+    /// Shipments polled per run, those checked longest ago first
+    #[serde(default = "default_tracking_poll_batch_size")]
+    pub poll_batch_size: i64,
+    
+    /// Stop polling shipments this many days after their label was bought
+    #[serde(default = "default_tracking_stop_after_days")]
+    pub stop_after_days: i64,
 }
 
-/// Shipping configuration
+fn default_tracking_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_tracking_poll_interval_secs() -> u64 {
+    3600
+}
+
+fn default_tracking_poll_batch_size() -> i64 {
+    200
+}
+
+fn default_tracking_stop_after_days() -> i64 {
+    60
+}
+
+impl Default for TrackingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            webhook_token: String::new(),
+            public_url: default_tracking_public_url(),
+            poll_interval_secs: default_tracking_poll_interval_secs(),
+            poll_batch_size: default_tracking_poll_batch_size(),
+            stop_after_days: default_tracking_stop_after_days(),
+        }
+    }
+}
+
+/// Carrier accounts and origin address of one inventory location
+///
+/// Carriers the location does not list ship on the store-wide account;
+/// a carrier listed with `enabled = false` is not used from the location.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct ShippingConfig {
-    /// Default shipping provider
-    #[serde(default = "default_shipping_provider")]
-    pub default_provider: String,
-    
-    /// Enable test mode for shipping (use sandbox APIs)
-    #[serde(default)]
-    pub test_mode: bool,
+pub struct LocationShippingConfig {
+    /// Code of the inventory location
+    pub location: String,
     
-    /// Default shipping origin address
+    /// Where parcels from the location ship from (default `[shipping] origin`)
     #[serde(default)]
     pub origin: Option<ShippingOriginConfig>,
     
-    /// DHL Express configuration
     #[serde(default)]
-    pub dhl: DhlConfig,
+    pub dhl: Option<DhlConfig>,
     
-    /// FedEx configuration
     #[serde(default)]
-    pub fedex: FedExConfig,
+    pub fedex: Option<FedExConfig>,
     
-    /// UPS configuration
     #[serde(default)]
-    pub ups: UpsConfig,
+    pub ups: Option<UpsConfig>,
     
-    /// USPS configuration
     #[serde(default)]
-    pub usps: UspsConfig,
+    pub usps: Option<UspsConfig>,
 }
 
 fn default_shipping_provider() -> String {
@@ -1434,6 +5943,31 @@ impl Default for ShippingOriginConfig {
     }
 }
 
+impl ShippingOriginConfig {
+    /// As a carrier address (the store name goes in `first_name` and `company`)
+    pub fn to_address(&self) -> crate::common::Address {
+        let now = chrono::Utc::now();
+        crate::common::Address {
+            id: uuid::Uuid::nil(),
+            customer_id: uuid::Uuid::nil(),
+            first_name: self.name.clone(),
+            last_name: String::new(),
+            company: Some(self.name.clone()),
+            phone: self.phone.clone(),
+            address1: self.address1.clone(),
+            address2: self.address2.clone(),
+            city: self.city.clone(),
+            state: Some(self.state.clone()),
+            country: self.country.clone(),
+            zip: self.zip.clone(),
+            is_default_shipping: false,
+            is_default_billing: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
 /// DHL Express configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DhlConfig {
@@ -1515,6 +6049,31 @@ pub struct UspsConfig {
     pub sandbox: bool,
 }
 
+/// EasyPost configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EasyPostConfig {
+    /// Enable EasyPost
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// EasyPost API key
+    pub api_key: Option<String>,
+}
+
+/// ShipStation configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ShipStationConfig {
+    /// Enable ShipStation
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// ShipStation API key
+    pub api_key: Option<String>,
+    
+    /// ShipStation API secret
+    pub api_secret: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1563,7 +6122,372 @@ mod tests {
         config.security.jwt.secret = "this_is_a_very_secure_random_key_for_testing".to_string();
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_oauth_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        config.security.oauth.github = Some(OAuthProviderConfig {
+            client_id: "Iv1.abc".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_uri: "https://api.example.com/api/v1/auth/oauth/github/callback".to_string(),
+            scopes: Vec::new(),
+            team_id: None,
+            key_id: None,
+            private_key_path: None,
+        });
+        // Logins need somewhere to return to
+        assert!(config.validate().is_err());
+
+        config.security.oauth.allowed_return_urls = vec!["https://shop.example.com/account".to_string()];
+        assert!(config.validate().is_ok());
+
+        // Apple signs its client secret with a key instead
+        config.security.oauth.apple = config.security.oauth.github.clone();
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_jwt_token_lifetimes() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        assert_eq!(config.security.jwt.access_token_ttl_secs(), 24 * 3600);
+
+        config.security.jwt.access_token_ttl_mins = Some(15);
+        assert_eq!(config.security.jwt.access_token_ttl_secs(), 15 * 60);
+        assert!(config.validate().is_ok());
+
+        config.security.jwt.refresh_token_idle_days = 60;
+        assert!(config.validate().is_err());
+
+        config.security.jwt.refresh_token_idle_days = 7;
+        config.security.jwt.access_token_ttl_mins = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_email_provider_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        
+        let email: EmailConfig = toml::from_str("provider = \"sendgrid\"").unwrap();
+        assert_eq!(email.provider, EmailProvider::SendGrid);
+        config.notifications.email = email;
+        assert!(config.validate().is_err());
+        
+        config.notifications.email.sendgrid = Some(SendGridConfig {
+            api_key: "SG.test".to_string(),
+            webhook_public_key: None,
+            api_url: default_sendgrid_api_url(),
+        });
+        assert!(config.validate().is_ok());
+        
+        config.notifications.email.provider = EmailProvider::Ses;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_email_branding_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+
+        let email: EmailConfig = toml::from_str("[branding]\nbrand_color = \"#1a2b3c\"\nlogo_url = \"https://example.com/logo.png\"").unwrap();
+        assert_eq!(email.branding.company_name, "R Commerce");
+        config.notifications.email = email;
+        assert!(config.validate().is_ok());
+
+        config.notifications.email.branding.brand_color = "red".to_string();
+        assert!(config.validate().is_err());
+        config.notifications.email.branding.brand_color = "#abc".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_fraud_config() {
+        use crate::models::RiskAction;
+        
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        
+        let fraud: FraudConfig = toml::from_str("enabled = true\ncancel_score = 90").unwrap();
+        assert_eq!(fraud.review_score, Some(50));
+        assert_eq!(fraud.action(0), RiskAction::Allow);
+        assert_eq!(fraud.action(30), RiskAction::Require3ds);
+        assert_eq!(fraud.action(65), RiskAction::Review);
+        assert_eq!(fraud.action(95), RiskAction::Cancel);
+        config.fraud = fraud;
+        assert!(config.validate().is_ok());
+        
+        // Unset actions are never taken
+        config.fraud.review_score = None;
+        assert_eq!(config.fraud.action(65), RiskAction::Require3ds);
+        assert!(config.validate().is_ok());
+        
+        // Thresholds rise with severity
+        config.fraud.cancel_score = Some(20);
+        assert!(config.validate().is_err());
+        config.fraud.cancel_score = Some(101);
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_landed_cost_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        
+        let landed_cost: LandedCostConfig = toml::from_str(
+            r#"
+            enabled = true
+            default_incoterm = "DDP"
+            de_minimis = { US = 800 }
+            import_tax_rates = { GB = 0.20 }
+            [[duty_rates]]
+            country = "US"
+            hs_code = "6109"
+            rate = 0.165
+            "#,
+        )
+        .unwrap();
+        assert!(landed_cost.offer_ddp);
+        assert_eq!(landed_cost.default_incoterm, crate::tax::Incoterm::Ddp);
+        config.landed_cost = landed_cost;
+        assert!(config.validate().is_ok());
+        
+        // HS codes are digits
+        config.landed_cost.duty_rates[0].hs_code = "61.09".to_string();
+        assert!(config.validate().is_err());
+        config.landed_cost.duty_rates[0].hs_code = "6109".to_string();
+        
+        // Rates are fractions
+        config.landed_cost.import_tax_rates.insert("AU".to_string(), rust_decimal::Decimal::from(10));
+        assert!(config.validate().is_err());
+        config.landed_cost.import_tax_rates.remove("AU");
+        
+        // The default must be offered
+        config.landed_cost.offer_ddp = false;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_purchase_order_dispatch_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        assert!(config.validate().is_ok());
+
+        config.purchasing.price_tolerance_percent = rust_decimal::Decimal::NEGATIVE_ONE;
+        assert!(config.validate().is_err());
+        config.purchasing.price_tolerance_percent = rust_decimal::Decimal::ONE;
+
+        config.purchasing.public_url = "shop.example.com".to_string();
+        assert!(config.validate().is_err());
+        config.purchasing.public_url = "https://shop.example.com".to_string();
+
+        config.purchasing.max_dispatch_attempts = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_supplier_feeds_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        assert!(config.validate().is_ok());
+
+        config.supplier_feeds.dropship_interval_secs = 30;
+        assert!(config.validate().is_err());
+        config.supplier_feeds.dropship_interval_secs = 120;
+
+        config.supplier_feeds.max_feed_bytes = 1024 * 1024 * 1024;
+        assert!(config.validate().is_err());
+        config.supplier_feeds.max_feed_bytes = 1024;
+        assert!(config.validate().is_ok());
+
+        config.supplier_feeds.max_send_attempts = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_printing_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+
+        let printing: PrintingConfig = toml::from_str(
+            r#"
+            label_profile = "zebra"
+            [[profiles]]
+            name = "zebra"
+            paper = "label_4x6"
+            print_command = "lp -d zebra-1"
+            [[profiles]]
+            name = "a4"
+            paper = "a4"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(printing.profile("Zebra").unwrap().paper, PaperSize::Label4x6);
+        config.printing = printing;
+        assert!(config.validate().is_ok());
+
+        // Default profiles must be defined
+        config.printing.document_profile = "letter".to_string();
+        assert!(config.validate().is_err());
+        config.printing.document_profile = "a4".to_string();
+
+        // Names are unique
+        config.printing.profiles[1].name = "ZEBRA".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_metric_alerts_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        
+        let observability: ObservabilityConfig = toml::from_str(
+            r#"
+            [alerts]
+            [[alerts.rules]]
+            name = "payment_failures"
+            metric = "payment_failure_rate"
+            above = 10.0
+            for_secs = 300
+            "#,
+        )
+        .unwrap();
+        assert_eq!(observability.metrics_window_secs, 300);
+        assert_eq!(observability.alerts.rules[0].window_secs, 300);
+        assert_eq!(observability.alerts.rules[0].min_samples, 10);
+        config.observability = observability;
+        assert!(config.validate().is_ok());
+        
+        // Rates are percentages
+        config.observability.alerts.rules[0].above = Some(150.0);
+        assert!(config.validate().is_err());
+        
+        // Exactly one of above and below
+        config.observability.alerts.rules[0].below = Some(1.0);
+        config.observability.alerts.rules[0].above = Some(10.0);
+        assert!(config.validate().is_err());
+        config.observability.alerts.rules[0].below = None;
+        
+        // Someone to notify
+        config.observability.alerts.alert_roles.clear();
+        assert!(config.validate().is_err());
+        config.observability.alerts.slack_webhook_url = Some("https://hooks.slack.com/services/T0/B0/X".to_string());
+        assert!(config.validate().is_ok());
+        
+        let duplicate = config.observability.alerts.rules[0].clone();
+        config.observability.alerts.rules.push(duplicate);
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_anomaly_detection_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        config.observability.anomalies.bucket_secs = 10;
+        // Not checked while disabled
+        assert!(config.validate().is_ok());
+        
+        config.observability.anomalies.enabled = true;
+        assert!(config.validate().is_err());
+        config.observability.anomalies.bucket_secs = 900;
+        assert!(config.validate().is_ok());
+        
+        config.observability.anomalies.baseline_buckets = 4;
+        assert!(config.validate().is_err());
+        config.observability.anomalies.baseline_buckets = 96;
+        
+        config.observability.anomalies.z_threshold = 0.0;
+        assert!(config.validate().is_err());
+        config.observability.anomalies.z_threshold = 3.0;
+        
+        // Someone to notify
+        config.observability.alerts.alert_roles.clear();
+        assert!(config.validate().is_err());
+    }
     
+    #[test]
+    fn test_media_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        assert!(config.validate().is_ok());
+        
+        config.media.storage_type = StorageType::S3;
+        assert!(config.validate().is_err());
+        config.media.s3_bucket = Some("products".to_string());
+        config.media.s3_region = Some("us-east-1".to_string());
+        config.media.s3_access_key = Some("key".to_string());
+        config.media.s3_secret_key = Some("secret".to_string());
+        assert!(config.validate().is_ok());
+        
+        config.media.image_processing.sizes.push(ImageSize {
+            name: "thumbnail".to_string(),
+            width: 100,
+            height: 100,
+            crop: true,
+        });
+        assert!(config.validate().is_err());
+        config.media.image_processing.sizes.pop();
+        
+        config.media.image_processing.sizes[0].name = "thumb/../x".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_trusted_proxies_validation() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        config.server.trusted_proxies = vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.server.trusted_proxies.push("proxy.internal".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_job_retry_policy() {
+        let mut config = Config::default();
+        config.security.jwt.secret = "a".repeat(32);
+        config.scheduler.retry_policies.insert(
+            "marketplace_sync".to_string(),
+            JobRetryPolicy { max_attempts: 3, initial_delay_secs: 30, max_delay_secs: 100, multiplier: 3.0 },
+        );
+        assert!(config.validate().is_ok());
+
+        let policy = config.scheduler.retry_policy("marketplace_sync");
+        assert_eq!(policy.retry_delay(1), Some(std::time::Duration::from_secs(30)));
+        assert_eq!(policy.retry_delay(2), Some(std::time::Duration::from_secs(90)));
+        assert_eq!(policy.retry_delay(3), None);
+        let default = config.scheduler.retry_policy("subscription_billing");
+        assert_eq!(default.retry_delay(3), Some(std::time::Duration::from_secs(240)));
+        assert_eq!(default.retry_delay(4), None);
+
+        config.scheduler.retry.max_attempts = 0;
+        assert!(config.validate().is_err());
+        config.scheduler.retry.max_attempts = 1;
+        assert_eq!(config.scheduler.retry.retry_delay(1), None);
+        config.scheduler.retry_policies.get_mut("marketplace_sync").unwrap().multiplier = 0.5;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_admin_ui_path_validation() {
+        let mut config = AdminUiConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.path = "/staff/console".to_string();
+        assert!(config.validate().is_ok());
+
+        for path in ["admin", "/", "/admin/", "/api/admin", "/admin/:id"] {
+            config.path = path.to_string();
+            assert!(config.validate().is_err(), "{} should be rejected", path);
+        }
+
+        // Not checked when the UI is off
+        config.enabled = false;
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_tls_config_defaults() {
         let tls_config = TlsConfig::default();
@@ -1674,6 +6598,27 @@ pub struct TaxConfig {
     #[serde(default = "default_vat_cache_days")]
     pub vat_cache_days: i64,
     
+    /// Seconds VIES answers are also kept in memory
+    #[serde(default = "default_vat_cache_ttl_secs")]
+    pub vat_cache_ttl_secs: u64,
+    
+    /// Give up on a VIES request after this many seconds, so checkout isn't held up
+    #[serde(default = "default_vies_timeout_secs")]
+    pub vies_timeout_secs: u64,
+    
+    /// Accept well-formed VAT IDs provisionally while VIES is unavailable and
+    /// re-verify them with the `vat_reverification` job
+    #[serde(default = "default_true")]
+    pub accept_vat_ids_when_vies_unavailable: bool,
+    
+    /// Seconds before the first re-verification; doubles after each failed attempt
+    #[serde(default = "default_vat_reverify_interval_secs")]
+    pub vat_reverify_interval_secs: u64,
+    
+    /// Re-verification attempts before giving up on a VAT ID
+    #[serde(default = "default_vat_reverify_max_attempts")]
+    pub vat_reverify_max_attempts: i32,
+    
     /// Avalara configuration
     #[serde(default)]
     pub avalara: Option<AvalaraConfig>,
@@ -1693,6 +6638,11 @@ impl Default for TaxConfig {
             default_tax_zone: None,
             validate_vat_ids: default_validate_vat(),
             vat_cache_days: default_vat_cache_days(),
+            vat_cache_ttl_secs: default_vat_cache_ttl_secs(),
+            vies_timeout_secs: default_vies_timeout_secs(),
+            accept_vat_ids_when_vies_unavailable: true,
+            vat_reverify_interval_secs: default_vat_reverify_interval_secs(),
+            vat_reverify_max_attempts: default_vat_reverify_max_attempts(),
             avalara: None,
             taxjar: None,
         }
@@ -1711,6 +6661,22 @@ fn default_vat_cache_days() -> i64 {
     30
 }
 
+fn default_vat_cache_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_vies_timeout_secs() -> u64 {
+    5
+}
+
+fn default_vat_reverify_interval_secs() -> u64 {
+    900
+}
+
+fn default_vat_reverify_max_attempts() -> i32 {
+    8
+}
+
 /// Avalara AvaTax configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AvalaraConfig {
@@ -1,6 +1,7 @@
 //! Database access utilities
 
 pub mod migrate;
+pub mod partitions;
 
 use sqlx::PgPool;
 use std::sync::Arc;
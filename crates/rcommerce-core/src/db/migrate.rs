@@ -15,6 +15,7 @@
 use sqlx::{PgPool, Row};
 use tracing::{info, warn, error};
 
+use crate::db::partitions::{PartitionManager, PARTITION_MONTHS_AHEAD};
 use crate::{Error, Result};
 
 /// Migration record tracking applied migrations
@@ -25,6 +26,90 @@ pub struct Migration {
     pub applied_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// All known migrations as (version, name, sql)
+/// Note: Migration 1 is a comprehensive schema creation; later migrations add features on top
+const MIGRATIONS: &[(i64, &str, &str)] = &[
+    (1, "complete_schema", include_str!("../../migrations/001_complete_schema.sql")),
+    (3, "inventory_notifications_fulfillment", include_str!("../../migrations/003_inventory_notifications_fulfillment.sql")),
+    (4, "exchange_rates", include_str!("../../migrations/004_exchange_rates.sql")),
+    (5, "payment_receipts", include_str!("../../migrations/005_payment_receipts.sql")),
+    (6, "price_history", include_str!("../../migrations/006_price_history.sql")),
+    (7, "order_archive", include_str!("../../migrations/007_order_archive.sql")),
+    (8, "product_variant_options", include_str!("../../migrations/008_product_variant_options.sql")),
+    (9, "table_partitioning", include_str!("../../migrations/009_table_partitioning.sql")),
+    (10, "webhook_replays", include_str!("../../migrations/010_webhook_replays.sql")),
+    (11, "webhook_transforms", include_str!("../../migrations/011_webhook_transforms.sql")),
+    (12, "subscription_plans", include_str!("../../migrations/012_subscription_plans.sql")),
+    (13, "stock_adjustment_requests", include_str!("../../migrations/013_stock_adjustment_requests.sql")),
+    (14, "pos_registers", include_str!("../../migrations/014_pos_registers.sql")),
+    (15, "price_lists", include_str!("../../migrations/015_price_lists.sql")),
+    (16, "customer_address_book", include_str!("../../migrations/016_customer_address_book.sql")),
+    (17, "order_addons", include_str!("../../migrations/017_order_addons.sql")),
+    (18, "delivery_preferences", include_str!("../../migrations/018_delivery_preferences.sql")),
+    (19, "returns", include_str!("../../migrations/019_returns.sql")),
+    (20, "rbac", include_str!("../../migrations/020_rbac.sql")),
+    (21, "checkout_fields", include_str!("../../migrations/021_checkout_fields.sql")),
+    (22, "gift_cards", include_str!("../../migrations/022_gift_cards.sql")),
+    (23, "flash_sales", include_str!("../../migrations/023_flash_sales.sql")),
+    (24, "purchase_limits", include_str!("../../migrations/024_purchase_limits.sql")),
+    (25, "publishable_keys", include_str!("../../migrations/025_publishable_keys.sql")),
+    (26, "idempotency_keys", include_str!("../../migrations/026_idempotency_keys.sql")),
+    (27, "access_controls", include_str!("../../migrations/027_access_controls.sql")),
+    (28, "sealed_secrets", include_str!("../../migrations/028_sealed_secrets.sql")),
+    (29, "email_provider_events", include_str!("../../migrations/029_email_provider_events.sql")),
+    (30, "scheduled_jobs", include_str!("../../migrations/030_scheduled_jobs.sql")),
+    (31, "marketplace_sync", include_str!("../../migrations/031_marketplace_sync.sql")),
+    (32, "automation_rules", include_str!("../../migrations/032_automation_rules.sql")),
+    (33, "purchase_orders", include_str!("../../migrations/033_purchase_orders.sql")),
+    (34, "report_subscriptions", include_str!("../../migrations/034_report_subscriptions.sql")),
+    (35, "hosted_checkouts", include_str!("../../migrations/035_hosted_checkouts.sql")),
+    (36, "shipping_rules", include_str!("../../migrations/036_shipping_rules.sql")),
+    (37, "url_redirects", include_str!("../../migrations/037_url_redirects.sql")),
+    (38, "customer_groups", include_str!("../../migrations/038_customer_groups.sql")),
+    (39, "metric_alerts", include_str!("../../migrations/039_metric_alerts.sql")),
+    (40, "incidents", include_str!("../../migrations/040_incidents.sql")),
+    (41, "collections", include_str!("../../migrations/041_collections.sql")),
+    (42, "product_media", include_str!("../../migrations/042_product_media.sql")),
+    (43, "shipping_preferences", include_str!("../../migrations/043_shipping_preferences.sql")),
+    (44, "shipment_labels", include_str!("../../migrations/044_shipment_labels.sql")),
+    (45, "import_sync_cursors", include_str!("../../migrations/045_import_sync_cursors.sql")),
+    (46, "landed_cost", include_str!("../../migrations/046_landed_cost.sql")),
+    (47, "print_batches", include_str!("../../migrations/047_print_batches.sql")),
+    (48, "supplier_feeds", include_str!("../../migrations/048_supplier_feeds.sql")),
+    (49, "purchase_order_dispatch", include_str!("../../migrations/049_purchase_order_dispatch.sql")),
+    (50, "vat_validation_history", include_str!("../../migrations/050_vat_validation_history.sql")),
+    (51, "storefront_events", include_str!("../../migrations/051_storefront_events.sql")),
+    (52, "shipment_label_files", include_str!("../../migrations/052_shipment_label_files.sql")),
+    (53, "shipment_tracking", include_str!("../../migrations/053_shipment_tracking.sql")),
+    (54, "local_pickup", include_str!("../../migrations/054_local_pickup.sql")),
+    (55, "login_sessions", include_str!("../../migrations/055_login_sessions.sql")),
+    (56, "access_log", include_str!("../../migrations/056_access_log.sql")),
+    (57, "two_factor", include_str!("../../migrations/057_two_factor.sql")),
+    (58, "oauth_login", include_str!("../../migrations/058_oauth_login.sql")),
+    (59, "audit_log", include_str!("../../migrations/059_audit_log.sql")),
+    (60, "soft_deletes", include_str!("../../migrations/060_soft_deletes.sql")),
+    (61, "live_events", include_str!("../../migrations/061_live_events.sql")),
+    (62, "stock_events", include_str!("../../migrations/062_stock_events.sql")),
+    (63, "domain_events", include_str!("../../migrations/063_domain_events.sql")),
+    (64, "event_outbox", include_str!("../../migrations/064_event_outbox.sql")),
+    (65, "stock_reservation_commit", include_str!("../../migrations/065_stock_reservation_commit.sql")),
+    (66, "order_number_sequences", include_str!("../../migrations/066_order_number_sequences.sql")),
+    (67, "sales_analytics", include_str!("../../migrations/067_sales_analytics.sql")),
+    (68, "product_attributes", include_str!("../../migrations/068_product_attributes.sql")),
+    (69, "bundle_pricing", include_str!("../../migrations/069_bundle_pricing.sql")),
+    (70, "fraud_screening", include_str!("../../migrations/070_fraud_screening.sql")),
+    (71, "payment_sessions", include_str!("../../migrations/071_payment_sessions.sql")),
+    (72, "payment_captures", include_str!("../../migrations/072_payment_captures.sql")),
+    (73, "payout_reconciliation", include_str!("../../migrations/073_payout_reconciliation.sql")),
+    (74, "customer_account", include_str!("../../migrations/074_customer_account.sql")),
+    (75, "email_template_overrides", include_str!("../../migrations/075_email_template_overrides.sql")),
+    (76, "notification_suppression", include_str!("../../migrations/076_notification_suppression.sql")),
+    (77, "in_app_notifications", include_str!("../../migrations/077_in_app_notifications.sql")),
+    (78, "job_dead_letters", include_str!("../../migrations/078_job_dead_letters.sql")),
+    (79, "stock_reservation_recheck", include_str!("../../migrations/079_stock_reservation_recheck.sql")),
+    (80, "stock_reservation_unit_stock", include_str!("../../migrations/080_stock_reservation_unit_stock.sql")),
+];
+
 /// Database migration manager
 pub struct Migrator {
     pool: PgPool,
@@ -89,6 +174,23 @@ impl Migrator {
         Ok(())
     }
 
+    /// Get migrations that are known to this binary but not yet applied
+    pub async fn pending_migrations(&self) -> Result<Vec<(i64, &'static str)>> {
+        self.init_migration_table().await?;
+        let applied = self.get_applied_migrations().await?;
+
+        Ok(MIGRATIONS
+            .iter()
+            .filter(|(version, _, _)| !applied.iter().any(|m| m.version == *version))
+            .map(|&(version, name, _)| (version, name))
+            .collect())
+    }
+
+    /// Latest migration version known to this binary
+    pub fn latest_version() -> i64 {
+        MIGRATIONS.iter().map(|(version, _, _)| *version).max().unwrap_or(0)
+    }
+
     /// Run all pending migrations
     pub async fn migrate(&self) -> Result<()> {
         info!("Initializing migration system...");
@@ -97,13 +199,7 @@ impl Migrator {
         let applied = self.get_applied_migrations().await?;
         info!("Found {} applied migrations", applied.len());
 
-        // Define all migrations
-        // Note: Only migration 1 is needed - it's a comprehensive schema creation
-        let migrations = vec![
-            (1, "complete_schema", include_str!("../../migrations/001_complete_schema.sql")),
-        ];
-
-        for (version, name, sql) in migrations {
+        for &(version, name, sql) in MIGRATIONS {
             if applied.iter().any(|m| m.version == version) {
                 info!("Migration {} ({}) already applied, skipping", version, name);
                 continue;
@@ -126,6 +222,11 @@ impl Migrator {
             info!("Migration {} ({}) applied successfully", version, name);
         }
 
+        // Partitions for the coming months, in case the server has been down
+        PartitionManager::new(self.pool.clone())
+            .ensure_partitions(PARTITION_MONTHS_AHEAD)
+            .await?;
+
         info!("All migrations completed successfully!");
         Ok(())
     }
@@ -292,6 +393,7 @@ impl Migrator {
 
         Ok(DbStatus {
             applied_migrations: applied.len() as i64,
+            schema_version: applied.iter().map(|m| m.version).max().unwrap_or(0),
             product_count,
             customer_count,
             order_count,
@@ -303,6 +405,7 @@ impl Migrator {
 #[derive(Debug, Clone)]
 pub struct DbStatus {
     pub applied_migrations: i64,
+    pub schema_version: i64,
     pub product_count: i64,
     pub customer_count: i64,
     pub order_count: i64,
@@ -0,0 +1,72 @@
+//! Monthly range partition maintenance
+//!
+//! Tables registered in `partitioned_tables` (see migration 009) are range
+//! partitioned by month on `created_at`. Partitions must exist before rows for
+//! their month arrive, otherwise the rows land in the table's default
+//! partition. The migrator creates the upcoming partitions after migrating and
+//! the API server keeps them topped up daily.
+
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::{Error, Result};
+
+/// Months of partitions kept ready ahead of the current one
+pub const PARTITION_MONTHS_AHEAD: i32 = 3;
+
+/// A single partition of a partitioned table
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PartitionInfo {
+    pub parent: String,
+    pub partition: String,
+    /// Partition bound as reported by PostgreSQL, e.g. `FOR VALUES FROM (...) TO (...)` or `DEFAULT`
+    pub bounds: String,
+    /// Planner row estimate (-1 if the partition has not been analyzed)
+    pub estimated_rows: i64,
+}
+
+/// Creates and lists monthly partitions
+pub struct PartitionManager {
+    pool: PgPool,
+}
+
+impl PartitionManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create any missing partitions from the current month through
+    /// `months_ahead` months ahead, returning the names of those created
+    pub async fn ensure_partitions(&self, months_ahead: i32) -> Result<Vec<String>> {
+        let created: Vec<String> = sqlx::query_scalar("SELECT ensure_monthly_partitions($1)")
+            .bind(months_ahead)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to create partitions: {}", e)))?;
+
+        for partition in &created {
+            info!("Created partition {}", partition);
+        }
+        Ok(created)
+    }
+
+    /// List the partitions of every registered table
+    pub async fn list_partitions(&self) -> Result<Vec<PartitionInfo>> {
+        sqlx::query_as::<_, PartitionInfo>(
+            r#"
+            SELECT parent.relname::text AS parent,
+                   child.relname::text AS partition,
+                   pg_get_expr(child.relpartbound, child.oid) AS bounds,
+                   child.reltuples::bigint AS estimated_rows
+            FROM partitioned_tables t
+            JOIN pg_class parent ON parent.oid = to_regclass(t.table_name)
+            JOIN pg_inherits i ON i.inhparent = parent.oid
+            JOIN pg_class child ON child.oid = i.inhrelid
+            ORDER BY parent.relname, child.relname
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list partitions: {}", e)))
+    }
+}
@@ -0,0 +1,375 @@
+//! The event bus
+//!
+//! Events go to a broadcast channel that every handler reads with its own
+//! task, so a slow webhook endpoint never holds up the code that published
+//! the event. With a relay, events published here are also sent to a Redis
+//! channel, and events other instances sent there are delivered here as
+//! [`EventOrigin::Remote`]. Database triggers add the events they send on
+//! the `live_events` Postgres channel as [`EventOrigin::Database`].
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::{BusEvent, DomainEvent, EventOrigin, EventsConfig};
+use crate::cache::RedisPool;
+use crate::websocket::LIVE_EVENTS_CHANNEL;
+use crate::{Error, Result};
+
+/// Wait before connecting again after the Redis or Postgres connection is lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Which events a handler gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerScope {
+    /// Events published on this instance, so side effects run once
+    Once,
+    /// Every event this instance sees, e.g. to push to its own sockets
+    Everywhere,
+}
+
+impl HandlerScope {
+    pub fn accepts(&self, origin: EventOrigin) -> bool {
+        match self {
+            HandlerScope::Once => origin == EventOrigin::Local,
+            HandlerScope::Everywhere => true,
+        }
+    }
+}
+
+/// A subscriber of the bus
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    fn scope(&self) -> HandlerScope {
+        HandlerScope::Once
+    }
+
+    async fn handle(&self, event: &BusEvent) -> Result<()>;
+}
+
+/// Redis pub/sub between instances
+#[derive(Clone)]
+struct Relay {
+    redis: RedisPool,
+    channel: Arc<str>,
+    outgoing: mpsc::UnboundedSender<String>,
+    /// Taken by the task sending to Redis once the relay is started
+    pending: Arc<Mutex<Option<mpsc::UnboundedReceiver<String>>>>,
+}
+
+/// A notification of a database trigger
+#[derive(Debug, Deserialize)]
+struct TriggerEvent {
+    topic: String,
+    data: serde_json::Value,
+    at: DateTime<Utc>,
+}
+
+/// Typed in-process publish/subscribe of domain events
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+    instance: Arc<str>,
+    relay: Option<Relay>,
+}
+
+impl EventBus {
+    pub fn new(config: &EventsConfig) -> Self {
+        let (sender, _) = broadcast::channel(config.buffer_size.max(1));
+        Self {
+            sender,
+            instance: crate::jobs::recurring::default_instance_id().into(),
+            relay: None,
+        }
+    }
+
+    /// Relay events to and from the other instances on a Redis channel
+    pub fn with_redis(mut self, redis: RedisPool, channel: impl Into<String>) -> Self {
+        let (outgoing, pending) = mpsc::unbounded_channel();
+        self.relay = Some(Relay {
+            redis,
+            channel: channel.into().into(),
+            outgoing,
+            pending: Arc::new(Mutex::new(Some(pending))),
+        });
+        self
+    }
+
+    /// Id of this instance in relayed events
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Whether events are relayed between instances
+    pub fn is_relayed(&self) -> bool {
+        self.relay.is_some()
+    }
+
+    /// Receive every event, whatever its origin
+    pub fn receiver(&self) -> broadcast::Receiver<BusEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish an event to this instance's handlers and, with a relay, to
+    /// the other instances
+    pub fn publish(&self, event: DomainEvent) -> BusEvent {
+        self.publish_recorded(Uuid::new_v4(), event, Utc::now())
+    }
+
+    /// Publish an event recorded earlier, e.g. in the outbox, keeping its id
+    /// and time
+    pub fn publish_recorded(&self, id: Uuid, event: DomainEvent, at: DateTime<Utc>) -> BusEvent {
+        let event = BusEvent {
+            id,
+            event,
+            at,
+            instance: self.instance.to_string(),
+            origin: EventOrigin::Local,
+        };
+        if let Some(relay) = &self.relay {
+            match serde_json::to_string(&event) {
+                Ok(message) => {
+                    let _ = relay.outgoing.send(message);
+                }
+                Err(e) => tracing::error!("Failed to serialize {} event for the relay: {}", event.event.name(), e),
+            }
+        }
+        self.deliver(event.clone());
+        event
+    }
+
+    fn deliver(&self, event: BusEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Run a handler on every event it accepts, each in turn, for the
+    /// server's lifetime
+    pub fn subscribe(&self, handler: Arc<dyn EventHandler>) {
+        let mut receiver = self.receiver();
+        tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event handler {} fell behind and skipped {} events", handler.name(), skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if !handler.scope().accepts(event.origin) {
+                    continue;
+                }
+                if let Err(e) = handler.handle(&event).await {
+                    tracing::warn!(
+                        "Event handler {} failed on {} event {}: {}",
+                        handler.name(),
+                        event.event.name(),
+                        event.id,
+                        e
+                    );
+                }
+            }
+        });
+    }
+
+    /// Start relaying through Redis, if configured; only the first call
+    /// starts anything
+    pub fn spawn_relay(&self) {
+        let Some(relay) = &self.relay else {
+            return;
+        };
+        let Some(outgoing) = relay.pending.lock().ok().and_then(|mut pending| pending.take()) else {
+            return;
+        };
+        tokio::spawn(send_relayed(relay.clone(), outgoing));
+
+        let bus = self.clone();
+        let relay = relay.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = bus.receive_relayed(&relay).await {
+                    tracing::warn!("Event relay stopped, retrying in {:?}: {}", RECONNECT_DELAY, e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn receive_relayed(&self, relay: &Relay) -> Result<()> {
+        let mut pubsub = relay.redis.pubsub().await?;
+        pubsub
+            .subscribe(&*relay.channel)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to subscribe to {}: {}", relay.channel, e)))?;
+        tracing::info!("Relaying events on Redis channel {}", relay.channel);
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = match message.get_payload() {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!("Ignored unreadable relayed event: {}", e);
+                    continue;
+                }
+            };
+            match serde_json::from_str::<BusEvent>(&payload) {
+                Ok(event) if event.instance == *self.instance => {}
+                Ok(mut event) => {
+                    event.origin = EventOrigin::Remote;
+                    self.deliver(event);
+                }
+                Err(e) => tracing::warn!("Ignored malformed relayed event: {}", e),
+            }
+        }
+        Err(Error::Other("Redis subscription ended".to_string()))
+    }
+
+    /// Deliver the events database triggers send until the server stops,
+    /// listening again after a lost connection. Every instance gets them,
+    /// so they are not relayed.
+    pub async fn listen(&self, pool: PgPool) {
+        loop {
+            if let Err(e) = self.forward_triggers(&pool).await {
+                tracing::warn!("Database event listener stopped, retrying in {:?}: {}", RECONNECT_DELAY, e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn forward_triggers(&self, pool: &PgPool) -> Result<()> {
+        let mut listener = PgListener::connect_with(pool).await.map_err(Error::Database)?;
+        listener.listen(LIVE_EVENTS_CHANNEL).await.map_err(Error::Database)?;
+        tracing::info!("Listening for database events on {}", LIVE_EVENTS_CHANNEL);
+        loop {
+            let notification = listener.recv().await.map_err(Error::Database)?;
+            let event = serde_json::from_str::<TriggerEvent>(notification.payload())
+                .map_err(|e| Error::validation(format!("Malformed database event: {}", e)))
+                .and_then(|trigger| Ok((DomainEvent::from_parts(&trigger.topic, trigger.data)?, trigger.at)));
+            match event {
+                Ok((event, at)) => {
+                    self.deliver(BusEvent {
+                        id: Uuid::new_v4(),
+                        event,
+                        at,
+                        instance: self.instance.to_string(),
+                        origin: EventOrigin::Database,
+                    });
+                }
+                Err(e) => tracing::warn!("Ignored database event: {}", e),
+            }
+        }
+    }
+}
+
+/// Send this instance's events to Redis, connecting again after errors;
+/// events published while Redis is down are dropped
+async fn send_relayed(relay: Relay, mut outgoing: mpsc::UnboundedReceiver<String>) {
+    let mut connection = None;
+    while let Some(message) = outgoing.recv().await {
+        if connection.is_none() {
+            match relay.redis.get().await {
+                Ok(conn) => connection = Some(conn),
+                Err(e) => {
+                    tracing::warn!("Failed to relay event, Redis is unavailable: {}", e);
+                    continue;
+                }
+            }
+        }
+        if let Some(conn) = &connection {
+            if let Err(e) = conn.publish(&relay.channel, message.as_bytes()).await {
+                tracing::warn!("Failed to relay event: {}", e);
+                connection = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::LowStock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counter {
+        scope: HandlerScope,
+        handled: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl EventHandler for Counter {
+        fn name(&self) -> &'static str {
+            "counter"
+        }
+
+        fn scope(&self) -> HandlerScope {
+            self.scope
+        }
+
+        async fn handle(&self, _event: &BusEvent) -> Result<()> {
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn low_stock() -> DomainEvent {
+        DomainEvent::InventoryLowStock(LowStock {
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            location_id: Uuid::new_v4(),
+            available_quantity: 1,
+            reorder_point: 3,
+        })
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert!(HandlerScope::Once.accepts(EventOrigin::Local));
+        assert!(!HandlerScope::Once.accepts(EventOrigin::Remote));
+        assert!(!HandlerScope::Once.accepts(EventOrigin::Database));
+        assert!(HandlerScope::Everywhere.accepts(EventOrigin::Remote));
+    }
+
+    #[tokio::test]
+    async fn test_handlers_get_the_events_they_accept() {
+        let bus = EventBus::new(&EventsConfig::default());
+        let once = Arc::new(AtomicUsize::new(0));
+        let everywhere = Arc::new(AtomicUsize::new(0));
+        bus.subscribe(Arc::new(Counter { scope: HandlerScope::Once, handled: once.clone() }));
+        bus.subscribe(Arc::new(Counter { scope: HandlerScope::Everywhere, handled: everywhere.clone() }));
+        let mut receiver = bus.receiver();
+
+        let published = bus.publish(low_stock());
+        assert_eq!(published.origin, EventOrigin::Local);
+        assert_eq!(published.instance, bus.instance());
+        let mut remote = bus.publish(low_stock());
+        remote.origin = EventOrigin::Remote;
+        bus.deliver(remote);
+
+        for _ in 0..3 {
+            receiver.recv().await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(once.load(Ordering::SeqCst), 2);
+        assert_eq!(everywhere.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_relayed_events_round_trip() {
+        let bus = EventBus::new(&EventsConfig::default());
+        let event = bus.publish(low_stock());
+        let relayed: BusEvent = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+        assert_eq!(relayed, event);
+    }
+}
@@ -0,0 +1,138 @@
+//! Side effects of domain events
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{BusEvent, DomainEvent, EventHandler, EventsConfig, ShipmentStatusChanged};
+use crate::models::FulfillmentStatus;
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, WebhookDispatchRepository};
+use crate::secrets::SecretBox;
+use crate::services::{post_webhook, WebhookEnvelope};
+use crate::shipping::tracking::TrackedShipment;
+use crate::Result;
+
+/// POSTs events to the webhooks subscribed to them
+pub struct WebhookHandler {
+    repository: Arc<dyn WebhookDispatchRepository>,
+    client: reqwest::Client,
+    secrets: SecretBox,
+    timeout: Duration,
+}
+
+impl WebhookHandler {
+    pub fn new(repository: Arc<dyn WebhookDispatchRepository>, config: &EventsConfig) -> Self {
+        Self {
+            repository,
+            client: reqwest::Client::new(),
+            secrets: SecretBox::disabled(),
+            timeout: Duration::from_secs(config.webhook_timeout_secs),
+        }
+    }
+
+    /// Open sealed webhook signing secrets with these master keys
+    pub fn with_secrets(mut self, secrets: SecretBox) -> Self {
+        self.secrets = secrets;
+        self
+    }
+}
+
+#[async_trait]
+impl EventHandler for WebhookHandler {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &BusEvent) -> Result<()> {
+        let event_type = event.event.name();
+        let targets = self.repository.subscribed(event_type).await?;
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let mut envelope = WebhookEnvelope::new(event_type, event.event.data());
+        envelope.timestamp = event.at;
+        let payload = serde_json::to_value(&envelope).unwrap_or_default();
+        let headers = [("X-Webhook-Event-Id", event.id.to_string())];
+
+        for mut target in targets {
+            target.secret = match self.secrets.open(&target.secret) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    tracing::error!("Failed to open secret of webhook {}: {}", target.id, e);
+                    continue;
+                }
+            };
+            let delivery = post_webhook(&self.client, &target, event_type, &payload, &headers, self.timeout).await;
+            if !delivery.is_success() {
+                tracing::warn!(
+                    "Webhook {} failed for {} event {}: {}",
+                    target.id,
+                    event_type,
+                    event.id,
+                    delivery.error.as_deref().unwrap_or("non-success status")
+                );
+            }
+            self.repository.record_delivery(target.id, event_type, &payload, &delivery).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Queues the customer notices of events: parcel shipped and delivered
+pub struct NotificationHandler {
+    notifications: Arc<dyn NotificationRepository>,
+}
+
+impl NotificationHandler {
+    pub fn new(notifications: Arc<dyn NotificationRepository>) -> Self {
+        Self { notifications }
+    }
+
+    async fn parcel_notice(&self, shipment: &ShipmentStatusChanged) -> Result<()> {
+        if !shipment.email_notifications || shipment.status == shipment.previous_status {
+            return Ok(());
+        }
+        let status = match shipment.status.as_str() {
+            "shipped" => FulfillmentStatus::Shipped,
+            "delivered" => FulfillmentStatus::Delivered,
+            _ => return Ok(()),
+        };
+        let tracked = TrackedShipment {
+            id: shipment.shipment_id,
+            order_id: shipment.order_id,
+            order_number: shipment.order_number.clone(),
+            email: shipment.email.clone(),
+            email_notifications: shipment.email_notifications,
+            location_code: None,
+            tracking_company: shipment.tracking_company.clone(),
+            tracking_number: shipment.tracking_number.clone(),
+            tracking_url: shipment.tracking_url.clone(),
+            status,
+        };
+        let recipient = Recipient::email(shipment.email.clone(), None);
+        let notification = match status {
+            FulfillmentStatus::Shipped => NotificationFactory::parcel_shipped(&tracked, shipment.estimated_delivery, recipient),
+            _ => NotificationFactory::parcel_delivered(&tracked, recipient),
+        };
+        self.notifications.create(&notification).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for NotificationHandler {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    async fn handle(&self, event: &BusEvent) -> Result<()> {
+        match &event.event {
+            DomainEvent::ShipmentStatusChanged(shipment) => self.parcel_notice(shipment).await,
+            _ => Ok(()),
+        }
+    }
+}
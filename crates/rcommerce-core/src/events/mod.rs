@@ -0,0 +1,289 @@
+//! Domain events
+//!
+//! Order, payment, inventory and shipping code publish what happened to an
+//! [`EventBus`] as typed [`DomainEvent`]s, and the side effects subscribe to
+//! it: webhook deliveries ([`WebhookHandler`]), customer notices
+//! ([`NotificationHandler`]) and the admin live events socket. Publishers
+//! don't know their subscribers, so a new side effect is a new handler.
+//!
+//! With Redis configured the bus also relays events between instances, so a
+//! dashboard connected to one API server sees orders placed on another and
+//! shipments tracked by a worker. Side effects still run once, on the
+//! instance that published the event.
+//!
+//! Events of changes made in a transaction go through the [`outbox`]
+//! instead, so they are published once the transaction commits.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::websocket::StockUpdate;
+use crate::{Error, Result};
+
+pub mod bus;
+pub mod handlers;
+pub mod outbox;
+
+pub use bus::{EventBus, EventHandler, HandlerScope};
+pub use handlers::{NotificationHandler, WebhookHandler};
+pub use outbox::{OutboxConfig, OutboxRelay};
+
+/// Event bus configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsConfig {
+    /// Events a slow handler may fall behind by before it skips some
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+
+    /// Relay events to the other instances through Redis pub/sub
+    #[serde(default = "default_true")]
+    pub redis_relay: bool,
+
+    /// Redis channel of the relay
+    #[serde(default = "default_redis_channel")]
+    pub redis_channel: String,
+
+    /// POST events to the webhooks subscribed to them
+    #[serde(default = "default_true")]
+    pub webhooks: bool,
+
+    /// Timeout for each webhook delivery
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub webhook_timeout_secs: u64,
+
+    /// Relay of events written to the outbox in transactions
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: default_buffer_size(),
+            redis_relay: true,
+            redis_channel: default_redis_channel(),
+            webhooks: true,
+            webhook_timeout_secs: default_webhook_timeout_secs(),
+            outbox: OutboxConfig::default(),
+        }
+    }
+}
+
+impl EventsConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.buffer_size == 0 {
+            return Err(Error::Config("events.buffer_size must be positive".to_string()));
+        }
+        if self.redis_channel.trim().is_empty() {
+            return Err(Error::Config("events.redis_channel must not be empty".to_string()));
+        }
+        if self.webhook_timeout_secs == 0 {
+            return Err(Error::Config("events.webhook_timeout_secs must be positive".to_string()));
+        }
+        self.outbox.validate()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_buffer_size() -> usize {
+    1024
+}
+
+fn default_redis_channel() -> String {
+    "rcommerce:events".to_string()
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+/// Database label of a status enum (`ReadyForPickup` is `ready_for_pickup`),
+/// as the sqlx types of the status enums are `rename_all = "snake_case"`
+pub fn status_label(status: &impl fmt::Debug) -> String {
+    let mut label = String::new();
+    for (i, c) in format!("{:?}", status).chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                label.push('_');
+            }
+            label.push(c.to_ascii_lowercase());
+        } else {
+            label.push(c);
+        }
+    }
+    label
+}
+
+/// An order was placed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct OrderCreated {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub customer_id: Option<Uuid>,
+    pub email: String,
+    pub total: Decimal,
+    pub currency: String,
+    pub status: String,
+}
+
+/// A payment was made or changed status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PaymentUpdated {
+    /// Set for payments recorded in `payments`
+    pub payment_id: Option<Uuid>,
+    pub order_id: Uuid,
+    pub status: String,
+    pub previous_status: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub gateway: Option<String>,
+}
+
+/// Available stock at a location fell to its reorder point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LowStock {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub available_quantity: i32,
+    pub reorder_point: i32,
+}
+
+/// A shipment moved on, with what customer notices need
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ShipmentStatusChanged {
+    pub shipment_id: Uuid,
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub email: String,
+    /// The customer wants email about the order
+    pub email_notifications: bool,
+    pub status: String,
+    pub previous_status: String,
+    /// Carrier status, e.g. `in_transit`
+    pub tracking_status: Option<String>,
+    pub tracking_company: Option<String>,
+    pub tracking_number: Option<String>,
+    pub tracking_url: Option<String>,
+    pub estimated_delivery: Option<DateTime<Utc>>,
+}
+
+/// Something that happened in the store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data")]
+pub enum DomainEvent {
+    #[serde(rename = "order.created")]
+    OrderCreated(OrderCreated),
+    #[serde(rename = "payment.updated")]
+    PaymentUpdated(PaymentUpdated),
+    #[serde(rename = "inventory.low_stock")]
+    InventoryLowStock(LowStock),
+    #[serde(rename = "shipment.status_changed")]
+    ShipmentStatusChanged(ShipmentStatusChanged),
+    /// Published by database triggers (migration 062)
+    #[serde(rename = "stock.availability_changed")]
+    StockAvailabilityChanged(StockUpdate),
+}
+
+impl DomainEvent {
+    /// Every event type
+    pub const NAMES: [&'static str; 5] = [
+        "order.created",
+        "payment.updated",
+        "inventory.low_stock",
+        "shipment.status_changed",
+        "stock.availability_changed",
+    ];
+
+    /// Event type, also the webhook event and live topic name
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::OrderCreated(_) => "order.created",
+            DomainEvent::PaymentUpdated(_) => "payment.updated",
+            DomainEvent::InventoryLowStock(_) => "inventory.low_stock",
+            DomainEvent::ShipmentStatusChanged(_) => "shipment.status_changed",
+            DomainEvent::StockAvailabilityChanged(_) => "stock.availability_changed",
+        }
+    }
+
+    /// The event's data, without its type
+    pub fn data(&self) -> serde_json::Value {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(mut event)) => event.remove("data").unwrap_or_default(),
+            _ => serde_json::Value::Null,
+        }
+    }
+
+    /// Build an event from its type and data
+    pub fn from_parts(name: &str, data: serde_json::Value) -> Result<Self> {
+        serde_json::from_value(serde_json::json!({ "event": name, "data": data }))
+            .map_err(|e| Error::validation(format!("Invalid {} event: {}", name, e)))
+    }
+}
+
+/// Where a bus event came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EventOrigin {
+    /// Published on this instance
+    #[default]
+    Local,
+    /// Relayed from another instance
+    Remote,
+    /// Sent by a database trigger, seen by every instance
+    Database,
+}
+
+/// A published event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusEvent {
+    pub id: Uuid,
+    pub event: DomainEvent,
+    pub at: DateTime<Utc>,
+    /// Instance that published it
+    pub instance: String,
+    #[serde(skip)]
+    pub origin: EventOrigin,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FulfillmentStatus;
+
+    #[test]
+    fn test_event_names_and_data() {
+        let event = DomainEvent::InventoryLowStock(LowStock {
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            location_id: Uuid::new_v4(),
+            available_quantity: 2,
+            reorder_point: 5,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "inventory.low_stock");
+        assert_eq!(json["event"], event.name());
+        assert_eq!(event.data()["reorder_point"], 5);
+        assert_eq!(DomainEvent::from_parts(event.name(), event.data()).unwrap(), event);
+        assert!(DomainEvent::from_parts("order.deleted", serde_json::json!({})).is_err());
+
+        let stock = DomainEvent::from_parts(
+            "stock.availability_changed",
+            serde_json::json!({ "product_id": Uuid::new_v4(), "variant_id": null, "status": "out_of_stock" }),
+        )
+        .unwrap();
+        assert_eq!(stock.name(), "stock.availability_changed");
+    }
+
+    #[test]
+    fn test_status_label() {
+        assert_eq!(status_label(&FulfillmentStatus::ReadyForPickup), "ready_for_pickup");
+        assert_eq!(status_label(&FulfillmentStatus::Shipped), "shipped");
+    }
+}
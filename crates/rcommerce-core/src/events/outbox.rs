@@ -0,0 +1,274 @@
+//! Transactional outbox relay
+//!
+//! Code that changes the database in a transaction writes its events to the
+//! outbox in that transaction ([`enqueue_event`](crate::repository::enqueue_event))
+//! instead of publishing them, so a crash right after the commit can't lose
+//! them. The relay publishes pending events to the bus under their outbox id
+//! and marks them processed. Every instance runs one; claims are leased, so
+//! an event is only published twice when a relay dies before marking it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+
+use super::{DomainEvent, EventBus};
+use crate::repository::{OutboxRepository, OUTBOX_CHANNEL};
+use crate::{Error, Result};
+
+/// Outbox relay configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxConfig {
+    /// Look for pending events this often when no commit wakes the relay
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+
+    /// Events claimed at a time
+    #[serde(default = "default_batch_size")]
+    pub batch_size: i64,
+
+    /// Another relay may claim an event again after this long
+    #[serde(default = "default_lease_secs")]
+    pub lease_secs: u64,
+
+    /// Events that fail this many times are left in the outbox
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+
+    /// Keep processed events this long
+    #[serde(default = "default_retention_hours")]
+    pub retention_hours: i64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: default_poll_interval_ms(),
+            batch_size: default_batch_size(),
+            lease_secs: default_lease_secs(),
+            max_attempts: default_max_attempts(),
+            retention_hours: default_retention_hours(),
+        }
+    }
+}
+
+impl OutboxConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.poll_interval_ms == 0 {
+            return Err(Error::Config("events.outbox.poll_interval_ms must be positive".to_string()));
+        }
+        if self.batch_size <= 0 {
+            return Err(Error::Config("events.outbox.batch_size must be positive".to_string()));
+        }
+        if self.lease_secs == 0 {
+            return Err(Error::Config("events.outbox.lease_secs must be positive".to_string()));
+        }
+        if self.max_attempts <= 0 {
+            return Err(Error::Config("events.outbox.max_attempts must be positive".to_string()));
+        }
+        if self.retention_hours <= 0 {
+            return Err(Error::Config("events.outbox.retention_hours must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_batch_size() -> i64 {
+    100
+}
+
+fn default_lease_secs() -> u64 {
+    60
+}
+
+fn default_max_attempts() -> i32 {
+    10
+}
+
+fn default_retention_hours() -> i64 {
+    168
+}
+
+/// Publishes the outbox to the event bus
+pub struct OutboxRelay {
+    repository: Arc<dyn OutboxRepository>,
+    bus: EventBus,
+    config: OutboxConfig,
+}
+
+impl OutboxRelay {
+    pub fn new(repository: Arc<dyn OutboxRepository>, bus: EventBus, config: OutboxConfig) -> Self {
+        Self { repository, bus, config }
+    }
+
+    pub fn config(&self) -> &OutboxConfig {
+        &self.config
+    }
+
+    /// Publish one batch of pending events; returns how many were claimed
+    pub async fn relay_batch(&self) -> Result<usize> {
+        let entries = self
+            .repository
+            .claim(self.config.batch_size, self.config.lease_secs, self.config.max_attempts)
+            .await?;
+        let mut published = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            match DomainEvent::from_parts(&entry.event_type, entry.payload.clone()) {
+                Ok(event) => {
+                    self.bus.publish_recorded(entry.id, event, entry.created_at);
+                    published.push(entry.id);
+                }
+                Err(e) => {
+                    tracing::warn!("Outbox event {} (attempt {}) not published: {}", entry.id, entry.attempts, e);
+                    self.repository.mark_failed(entry.id, &e.to_string()).await?;
+                }
+            }
+        }
+        self.repository.mark_processed(&published).await?;
+        Ok(entries.len())
+    }
+
+    /// Publish pending events until the server stops, as soon as their
+    /// transaction commits or at the latest every poll interval
+    pub async fn run(&self, pool: PgPool) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+        let mut listener = None;
+        loop {
+            if listener.is_none() {
+                listener = match listen(&pool).await {
+                    Ok(listener) => Some(listener),
+                    Err(e) => {
+                        tracing::warn!("Outbox relay is polling, failed to listen for commits: {}", e);
+                        None
+                    }
+                };
+            }
+
+            loop {
+                match self.relay_batch().await {
+                    Ok(claimed) if claimed as i64 >= self.config.batch_size => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::warn!("Outbox relay failed: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            match listener.as_mut() {
+                Some(commits) => {
+                    tokio::select! {
+                        notification = commits.recv() => {
+                            if let Err(e) = notification {
+                                tracing::warn!("Outbox relay lost its commit listener: {}", e);
+                                listener = None;
+                            }
+                        }
+                        _ = tokio::time::sleep(poll_interval) => {}
+                    }
+                }
+                None => tokio::time::sleep(poll_interval).await,
+            }
+        }
+    }
+
+    /// Delete processed events past their retention; returns how many
+    pub async fn purge(&self) -> Result<u64> {
+        let before = Utc::now() - chrono::Duration::hours(self.config.retention_hours);
+        self.repository.purge_processed(before).await
+    }
+}
+
+async fn listen(pool: &PgPool) -> Result<PgListener> {
+    let mut listener = PgListener::connect_with(pool).await.map_err(Error::Database)?;
+    listener.listen(OUTBOX_CHANNEL).await.map_err(Error::Database)?;
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventsConfig, LowStock};
+    use crate::repository::OutboxEntry;
+    use async_trait::async_trait;
+    use chrono::DateTime;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct MemoryOutbox {
+        pending: Mutex<Vec<OutboxEntry>>,
+        processed: Mutex<Vec<Uuid>>,
+        failed: Mutex<Vec<Uuid>>,
+    }
+
+    #[async_trait]
+    impl OutboxRepository for MemoryOutbox {
+        async fn claim(&self, limit: i64, _lease_secs: u64, _max_attempts: i32) -> Result<Vec<OutboxEntry>> {
+            let mut pending = self.pending.lock().unwrap();
+            let count = (limit as usize).min(pending.len());
+            Ok(pending.drain(..count).collect())
+        }
+
+        async fn mark_processed(&self, ids: &[Uuid]) -> Result<()> {
+            self.processed.lock().unwrap().extend_from_slice(ids);
+            Ok(())
+        }
+
+        async fn mark_failed(&self, id: Uuid, _error: &str) -> Result<()> {
+            self.failed.lock().unwrap().push(id);
+            Ok(())
+        }
+
+        async fn purge_processed(&self, _before: DateTime<Utc>) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn entry(event_type: &str, payload: serde_json::Value) -> OutboxEntry {
+        OutboxEntry { id: Uuid::new_v4(), event_type: event_type.to_string(), payload, created_at: Utc::now(), attempts: 1 }
+    }
+
+    #[tokio::test]
+    async fn test_relay_publishes_under_the_outbox_id() {
+        let low_stock = DomainEvent::InventoryLowStock(LowStock {
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            location_id: Uuid::new_v4(),
+            available_quantity: 0,
+            reorder_point: 2,
+        });
+        let good = entry(low_stock.name(), low_stock.data());
+        let bad = entry("order.created", serde_json::json!({ "order_id": "not a uuid" }));
+        let outbox = Arc::new(MemoryOutbox::default());
+        outbox.pending.lock().unwrap().extend([good.clone(), bad.clone()]);
+
+        let bus = EventBus::new(&EventsConfig::default());
+        let mut receiver = bus.receiver();
+        let relay = OutboxRelay::new(outbox.clone(), bus, OutboxConfig::default());
+        assert_eq!(relay.relay_batch().await.unwrap(), 2);
+
+        let published = receiver.recv().await.unwrap();
+        assert_eq!(published.id, good.id);
+        assert_eq!(published.at, good.created_at);
+        assert_eq!(published.event, low_stock);
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(*outbox.processed.lock().unwrap(), vec![good.id]);
+        assert_eq!(*outbox.failed.lock().unwrap(), vec![bad.id]);
+        assert_eq!(relay.relay_batch().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(OutboxConfig::default().validate().is_ok());
+        let config = OutboxConfig { batch_size: 0, ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+}
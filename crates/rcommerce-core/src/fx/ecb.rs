@@ -0,0 +1,233 @@
+//! European Central Bank reference rates
+//!
+//! The ECB publishes euro reference rates once per working day. Rates are
+//! fetched on demand, cached for `[fx] cache_ttl_secs`, and cross rates
+//! between other currencies go through the euro. With a history repository
+//! attached, each fetch is also recorded as `ecb` rates against the base
+//! currency, so the order and payment rate snapshots pick them up.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rust_decimal::Decimal;
+use tokio::sync::RwLock;
+
+use super::FxRateProvider;
+use crate::config::FxConfig;
+use crate::models::{Currency, RecordExchangeRateRequest};
+use crate::repository::ExchangeRateRepository;
+use crate::{Error, Result};
+
+/// One day of ECB reference rates
+#[derive(Debug, Clone)]
+pub struct EcbRates {
+    pub date: NaiveDate,
+    /// Units of each currency per euro (including EUR = 1)
+    pub rates: HashMap<Currency, Decimal>,
+}
+
+impl EcbRates {
+    /// Rate converting one unit of `from` into `to`
+    pub fn cross(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        let from_rate = self.rates.get(&from)?;
+        let to_rate = self.rates.get(&to)?;
+        Some((to_rate / from_rate).round_dp(10))
+    }
+}
+
+/// Parse the ECB daily reference rate XML (`eurofxref-daily.xml`).
+/// Currencies the store does not support are skipped.
+pub fn parse_ecb_rates(xml: &str) -> Result<EcbRates> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut date = None;
+    let mut rates = HashMap::from([(Currency::EUR, Decimal::ONE)]);
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::Other(format!("Invalid ECB rate XML: {}", e)))?;
+        let element = match event {
+            Event::Start(ref e) | Event::Empty(ref e) if e.local_name().as_ref() == b"Cube" => e.clone(),
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let mut attributes = HashMap::new();
+        for attribute in element.attributes() {
+            let attribute = attribute.map_err(|e| Error::Other(format!("Invalid ECB rate XML: {}", e)))?;
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| Error::Other(format!("Invalid ECB rate XML: {}", e)))?;
+            attributes.insert(attribute.key.as_ref().to_vec(), value.into_owned());
+        }
+
+        if let Some(time) = attributes.get(b"time".as_slice()) {
+            date = Some(
+                NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                    .map_err(|e| Error::Other(format!("Invalid ECB rate date '{}': {}", time, e)))?,
+            );
+        }
+        if let (Some(currency), Some(rate)) = (attributes.get(b"currency".as_slice()), attributes.get(b"rate".as_slice())) {
+            let Ok(currency) = currency.parse::<Currency>() else {
+                continue;
+            };
+            let rate = Decimal::from_str(rate)
+                .map_err(|e| Error::Other(format!("Invalid ECB rate for {}: {}", currency, e)))?;
+            if rate > Decimal::ZERO {
+                rates.insert(currency, rate);
+            }
+        }
+    }
+
+    let date = date.ok_or_else(|| Error::Other("ECB rate XML has no reference date".to_string()))?;
+    Ok(EcbRates { date, rates })
+}
+
+/// Rate provider fetching ECB reference rates
+pub struct EcbRateProvider {
+    client: reqwest::Client,
+    url: String,
+    timeout: Duration,
+    cache_ttl: Duration,
+    cache: RwLock<Option<(Instant, EcbRates)>>,
+    history: Option<(Arc<dyn ExchangeRateRepository>, Currency)>,
+}
+
+impl EcbRateProvider {
+    pub fn new(config: &FxConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: config.ecb_url.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            cache: RwLock::new(None),
+            history: None,
+        }
+    }
+
+    /// Record each fetch in the exchange rate history against `base_currency`
+    pub fn with_history(mut self, history: Arc<dyn ExchangeRateRepository>, base_currency: Currency) -> Self {
+        self.history = Some((history, base_currency));
+        self
+    }
+
+    /// Current rates, fetched when the cache has expired
+    pub async fn latest(&self) -> Result<EcbRates> {
+        if let Some((fetched_at, ref rates)) = *self.cache.read().await {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return Ok(rates.clone());
+            }
+        }
+        self.refresh().await
+    }
+
+    /// Fetch rates now, replacing the cache
+    pub async fn refresh(&self) -> Result<EcbRates> {
+        let xml = self
+            .client
+            .get(&self.url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::Other(format!("Failed to fetch ECB rates: {}", e)))?
+            .text()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read ECB rates: {}", e)))?;
+        let rates = parse_ecb_rates(&xml)?;
+
+        let previous = self.cache.write().await.replace((Instant::now(), rates.clone()));
+        let is_new_day = previous.map_or(true, |(_, previous)| previous.date != rates.date);
+        if is_new_day {
+            self.record(&rates).await?;
+        }
+        Ok(rates)
+    }
+
+    async fn record(&self, rates: &EcbRates) -> Result<()> {
+        let Some((ref history, base)) = self.history else {
+            return Ok(());
+        };
+
+        for &currency in rates.rates.keys().filter(|&&c| c != base) {
+            let Some(rate) = rates.cross(currency, base) else {
+                continue;
+            };
+            history
+                .record(RecordExchangeRateRequest {
+                    base_currency: base,
+                    quote_currency: currency,
+                    rate,
+                    source: "ecb".to_string(),
+                    effective_at: None,
+                })
+                .await?;
+        }
+        tracing::info!("Recorded ECB reference rates for {}", rates.date);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FxRateProvider for EcbRateProvider {
+    fn name(&self) -> &str {
+        "ecb"
+    }
+
+    async fn rate(&self, from: Currency, to: Currency) -> Result<Option<Decimal>> {
+        if from == to {
+            return Ok(Some(Decimal::ONE));
+        }
+        Ok(self.latest().await?.cross(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const DAILY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+    <gesmes:subject>Reference rates</gesmes:subject>
+    <Cube>
+        <Cube time='2026-10-14'>
+            <Cube currency='USD' rate='1.0850'/>
+            <Cube currency='JPY' rate='162.40'/>
+            <Cube currency='GBP' rate='0.8680'/>
+            <Cube currency='CHF' rate='0.9410'/>
+        </Cube>
+    </Cube>
+</gesmes:Envelope>"#;
+
+    #[test]
+    fn test_parse_ecb_rates() {
+        let rates = parse_ecb_rates(DAILY).unwrap();
+        assert_eq!(rates.date, NaiveDate::from_ymd_opt(2026, 10, 14).unwrap());
+        assert_eq!(rates.rates[&Currency::EUR], Decimal::ONE);
+        assert_eq!(rates.rates[&Currency::USD], dec!(1.0850));
+        // CHF is not a store currency
+        assert_eq!(rates.rates.len(), 4);
+    }
+
+    #[test]
+    fn test_cross_rates_go_through_euro() {
+        let rates = parse_ecb_rates(DAILY).unwrap();
+        assert_eq!(rates.cross(Currency::EUR, Currency::USD), Some(dec!(1.0850)));
+        assert_eq!(rates.cross(Currency::USD, Currency::GBP), Some(dec!(0.8000000000)));
+        assert_eq!(rates.cross(Currency::USD, Currency::CAD), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_date() {
+        assert!(parse_ecb_rates("<Cube><Cube currency='USD' rate='1.08'/></Cube>").is_err());
+    }
+}
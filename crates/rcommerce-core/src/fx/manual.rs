@@ -0,0 +1,48 @@
+//! Manual FX rates
+//!
+//! Uses the latest rate recorded in the exchange rate history for the pair,
+//! in either direction.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use super::FxRateProvider;
+use crate::models::Currency;
+use crate::repository::ExchangeRateRepository;
+use crate::Result;
+
+/// Rate provider backed by the recorded exchange rate history
+pub struct ManualRateProvider<R: ExchangeRateRepository> {
+    history: R,
+}
+
+impl<R: ExchangeRateRepository> ManualRateProvider<R> {
+    pub fn new(history: R) -> Self {
+        Self { history }
+    }
+}
+
+#[async_trait]
+impl<R: ExchangeRateRepository> FxRateProvider for ManualRateProvider<R> {
+    fn name(&self) -> &str {
+        "manual"
+    }
+
+    async fn rate(&self, from: Currency, to: Currency) -> Result<Option<Decimal>> {
+        if from == to {
+            return Ok(Some(Decimal::ONE));
+        }
+
+        let now = Utc::now();
+        // Stored rates are "1 quote = rate base"
+        if let Some(rate) = self.history.rate_at(to, from, now).await? {
+            return Ok(Some(rate.rate));
+        }
+        Ok(self
+            .history
+            .rate_at(from, to, now)
+            .await?
+            .map(|rate| (Decimal::ONE / rate.rate).round_dp(10)))
+    }
+}
@@ -0,0 +1,70 @@
+//! Foreign exchange rates
+//!
+//! Rate providers convert amounts between store currencies, e.g. to price a
+//! product that has no price list price in the shopper's currency, or to
+//! report an order's totals in the base currency. Providers are selected
+//! with `[fx] provider`:
+//!
+//! - `manual` - the rate history recorded through `/api/v1/admin/exchange-rates`
+//! - `ecb` - the European Central Bank's daily reference rates, fetched and
+//!   cached, and recorded in the same history so order snapshots use them
+
+pub mod ecb;
+pub mod manual;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use crate::config::{FxConfig, FxProvider};
+use crate::models::Currency;
+use crate::repository::PostgresExchangeRateRepository;
+use crate::Result;
+
+pub use ecb::{parse_ecb_rates, EcbRateProvider, EcbRates};
+pub use manual::ManualRateProvider;
+
+/// Source of exchange rates
+#[async_trait]
+pub trait FxRateProvider: Send + Sync {
+    /// Provider name, recorded with converted prices
+    fn name(&self) -> &str;
+
+    /// Rate converting one unit of `from` into `to`, or None if the pair is
+    /// unknown
+    async fn rate(&self, from: Currency, to: Currency) -> Result<Option<Decimal>>;
+}
+
+/// Convert an amount at a rate, rounded to cents
+pub fn convert_amount(amount: Decimal, rate: Decimal) -> Decimal {
+    (amount * rate).round_dp(2)
+}
+
+/// Build the configured rate provider
+pub fn provider_from_config(config: &FxConfig, db: sqlx::PgPool) -> Arc<dyn FxRateProvider> {
+    let history = PostgresExchangeRateRepository::new(db);
+    match config.provider {
+        FxProvider::Manual => Arc::new(ManualRateProvider::new(history)),
+        FxProvider::Ecb => {
+            let provider = EcbRateProvider::new(config);
+            if config.record_fetched_rates {
+                Arc::new(provider.with_history(Arc::new(history), config.base_currency()))
+            } else {
+                Arc::new(provider)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_convert_amount_rounds_to_cents() {
+        assert_eq!(convert_amount(dec!(19.99), dec!(0.9213)), dec!(18.42));
+        assert_eq!(convert_amount(dec!(100), dec!(1)), dec!(100));
+    }
+}
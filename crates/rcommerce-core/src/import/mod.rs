@@ -13,13 +13,18 @@
 //! - CSV
 //! - JSON
 //! - XML
+//!
+//! Platform imports can run as delta syncs of what changed since a time
+//! or since the last sync ([`sync::SyncCursors`]).
 
 pub mod error;
 pub mod formats;
 pub mod platforms;
+pub mod sync;
 pub mod types;
 
 pub use error::{ImportError, ImportResult};
+pub use sync::{parse_since, SyncCursors};
 pub use types::{ImportConfig, ImportProgress, ImportStats};
 
 use async_trait::async_trait;
@@ -4,3 +4,42 @@ pub mod magento;
 pub mod medusa;
 pub mod shopify;
 pub mod woocommerce;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::import::{error::ImportResult, types::ImportConfig};
+use crate::models::AddProductImageRequest;
+use crate::repository::PostgresProductImageRepository;
+use crate::services::MediaService;
+
+/// Downloads imported products' images into media storage
+pub(crate) struct ImageImporter {
+    media: MediaService<PostgresProductImageRepository>,
+}
+
+impl ImageImporter {
+    /// None when the import has no media storage configured
+    pub(crate) fn from_config(config: &ImportConfig, pool: &PgPool) -> ImportResult<Option<Self>> {
+        let Some(media) = &config.media else {
+            return Ok(None);
+        };
+        let media = MediaService::from_config(PostgresProductImageRepository::new(pool.clone()), media.clone())?;
+        Ok(Some(Self { media }))
+    }
+
+    /// Add a product's images (URL and alt text) in order; images imported
+    /// before are kept as they are, and ones that fail are logged and skipped
+    pub(crate) async fn attach(&self, product_id: Uuid, images: Vec<(String, Option<String>)>) {
+        for (url, alt_text) in images {
+            let request = AddProductImageRequest {
+                url: url.clone(),
+                alt_text: alt_text.filter(|text| !text.trim().is_empty()),
+                variant_id: None,
+            };
+            if let Err(e) = self.media.import_image(product_id, request).await {
+                tracing::warn!("Failed to import image {} for product {}: {}", url, product_id, e);
+            }
+        }
+    }
+}
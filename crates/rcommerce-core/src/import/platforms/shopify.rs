@@ -2,13 +2,24 @@
 
 use crate::import::{
     error::{ImportError, ImportResult},
+    platforms::ImageImporter,
+    sync::with_query,
     types::{ImportConfig, ImportProgress, ImportStats},
     PlatformImporter,
 };
+use crate::models::{
+    CollectionRule, CollectionRuleField, CollectionRuleRelation, CollectionType,
+    CreateCollectionRequest, UpdateCollectionRequest,
+};
+use crate::repository::{CollectionRepository, Database, PostgresCollectionRepository, ProductRepository};
+use crate::services::CollectionService;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
 use std::time::Duration;
+use uuid::Uuid;
 
 const SHOPIFY_API_VERSION: &str = "2024-01";
 
@@ -47,9 +58,9 @@ impl ShopifyImporter {
         let batch_size = 250.min(limit);
 
         loop {
-            let mut request_url = format!("{}?limit={}", url, batch_size);
+            let mut request_url = with_query(url, &format!("limit={}", batch_size));
             if let Some(ref pi) = page_info {
-                request_url = format!("{}&page_info={}", url, pi);
+                request_url = with_query(url, &format!("page_info={}", pi));
             }
 
             let response = self
@@ -113,6 +124,213 @@ impl ShopifyImporter {
 
         Ok(results)
     }
+
+    /// Create database pool from config
+    async fn create_pool(&self, database_url: &str) -> ImportResult<PgPool> {
+        use sqlx::postgres::PgPoolOptions;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await
+            .map_err(|e| ImportError::Database(crate::Error::Database(e)))?;
+
+        Ok(pool)
+    }
+
+    /// Import custom collections as manual collections (with the products
+    /// found by handle) and smart collections with their rules
+    async fn import_collections(
+        &self,
+        shop_domain: &str,
+        access_token: &str,
+        products: &[ShopifyProduct],
+        config: &ImportConfig,
+        progress: &(dyn Fn(ImportProgress) + Send + Sync),
+        stats: &mut ImportStats,
+    ) -> ImportResult<()> {
+        let custom: Vec<ShopifyCollection> = self
+            .fetch_paginated(&self.api_url(shop_domain, "custom_collections"), access_token, 250)
+            .await?;
+        let smart: Vec<ShopifyCollection> = self
+            .fetch_paginated(&self.api_url(shop_domain, "smart_collections"), access_token, 250)
+            .await?;
+        let collects: Vec<ShopifyCollect> = self
+            .fetch_paginated(&self.api_url(shop_domain, "collects"), access_token, 250)
+            .await?;
+
+        let mut requests = Vec::with_capacity(custom.len() + smart.len());
+        for collection in &custom {
+            requests.push((collection, collection.to_request(CollectionType::Manual, Vec::new())));
+        }
+        for collection in &smart {
+            match smart_collection_rules(&collection.rules) {
+                Ok(rules) => requests.push((collection, collection.to_request(CollectionType::Smart, rules))),
+                Err(e) => {
+                    stats.errors += 1;
+                    stats
+                        .error_details
+                        .push(format!("Smart collection '{}' not imported: {}", collection.title, e));
+                }
+            }
+        }
+
+        if config.options.dry_run {
+            tracing::info!("Would import {} Shopify collections", requests.len());
+            return Ok(());
+        }
+
+        let pool = self.create_pool(&config.database_url).await?;
+        let service = CollectionService::new(PostgresCollectionRepository::new(pool.clone()));
+        let product_repo = ProductRepository::new(Database::new(pool));
+        let product_handles: HashMap<u64, &str> = products.iter().map(|p| (p.id, p.handle.as_str())).collect();
+
+        for (i, (shopify_collection, request)) in requests.into_iter().enumerate() {
+            progress(ImportProgress {
+                stage: "collections".to_string(),
+                current: i + 1,
+                total: custom.len() + smart.len(),
+                message: format!("Importing collection: {}", shopify_collection.title),
+            });
+
+            let existing = service.repository().find_by_handle(&shopify_collection.handle).await?;
+            let imported = match existing {
+                Some(existing) if existing.collection_type == request.collection_type => {
+                    service
+                        .update_collection(
+                            existing.id,
+                            UpdateCollectionRequest {
+                                title: Some(request.title.clone()),
+                                description: request.description.clone(),
+                                rules: Some(request.rules.clone()).filter(|_| existing.collection_type == CollectionType::Smart),
+                                disjunctive: Some(request.disjunctive),
+                                sort_order: request.sort_order.clone(),
+                                published: Some(request.published),
+                                ..Default::default()
+                            },
+                        )
+                        .await
+                }
+                Some(_) => Err(crate::Error::validation(
+                    "a collection of the other type already has this handle",
+                )),
+                None => service.create_collection(request).await,
+            };
+            let collection = match imported {
+                Ok(collection) => collection,
+                Err(e) => {
+                    stats.errors += 1;
+                    let error_msg = format!("Failed to import collection '{}': {}", shopify_collection.title, e);
+                    tracing::error!("{}", error_msg);
+                    stats.error_details.push(error_msg);
+                    continue;
+                }
+            };
+
+            if collection.collection_type == CollectionType::Manual {
+                let mut members: Vec<&ShopifyCollect> = collects
+                    .iter()
+                    .filter(|collect| collect.collection_id == shopify_collection.id)
+                    .collect();
+                members.sort_by_key(|collect| collect.position);
+
+                let mut product_ids: Vec<Uuid> = Vec::with_capacity(members.len());
+                for handle in members.iter().filter_map(|collect| product_handles.get(&collect.product_id)) {
+                    if let Some(product) = product_repo.find_by_slug(handle).await? {
+                        product_ids.push(product.id);
+                    }
+                }
+                service.add_products(collection.id, &product_ids).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Download the images of products in the catalog with the same handle
+    async fn import_images(
+        &self,
+        products: &[ShopifyProduct],
+        config: &ImportConfig,
+        progress: &(dyn Fn(ImportProgress) + Send + Sync),
+    ) -> ImportResult<()> {
+        let pool = self.create_pool(&config.database_url).await?;
+        let Some(images) = ImageImporter::from_config(config, &pool)? else {
+            return Ok(());
+        };
+        let product_repo = ProductRepository::new(Database::new(pool));
+
+        for (i, shopify_product) in products.iter().enumerate() {
+            progress(ImportProgress {
+                stage: "images".to_string(),
+                current: i + 1,
+                total: products.len(),
+                message: format!("Importing images: {}", shopify_product.title),
+            });
+            if shopify_product.images.is_empty() {
+                continue;
+            }
+            let Some(product) = product_repo.find_by_slug(&shopify_product.handle).await? else {
+                continue;
+            };
+            let sources = shopify_product
+                .images
+                .iter()
+                .map(|image| (image.src.clone(), image.alt_text.clone()))
+                .collect();
+            images.attach(product.id, sources).await;
+        }
+        Ok(())
+    }
+}
+
+/// Filter for records updated after `options.since` (delta syncs)
+fn updated_since(config: &ImportConfig) -> String {
+    config
+        .options
+        .since
+        .map(|since| format!("updated_at_min={}", since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)))
+        .unwrap_or_default()
+}
+
+/// Our rules for a Shopify smart collection's; an error for rules on
+/// columns or relations we do not support (dropping one would change
+/// which products the collection holds)
+fn smart_collection_rules(rules: &[ShopifyCollectionRule]) -> Result<Vec<CollectionRule>, String> {
+    rules
+        .iter()
+        .map(|rule| {
+            let field = match rule.column.as_str() {
+                "tag" => CollectionRuleField::Tag,
+                "vendor" => CollectionRuleField::Vendor,
+                "variant_price" => CollectionRuleField::Price,
+                column => return Err(format!("rules on {} are not supported", column)),
+            };
+            let relation = match rule.relation.as_str() {
+                "equals" => CollectionRuleRelation::Equals,
+                "not_equals" => CollectionRuleRelation::NotEquals,
+                "greater_than" => CollectionRuleRelation::GreaterThan,
+                "less_than" => CollectionRuleRelation::LessThan,
+                "contains" => CollectionRuleRelation::Contains,
+                relation => return Err(format!("the {} relation is not supported", relation)),
+            };
+            let rule = CollectionRule::new(field, relation, rule.condition.clone());
+            rule.validate()?;
+            Ok(rule)
+        })
+        .collect()
+}
+
+/// Our sort order for a Shopify collection's; we have no sales ranking, so
+/// best-selling lists newest first
+fn collection_sort_order(sort_order: &str) -> Option<String> {
+    match sort_order {
+        "manual" | "alpha-asc" | "alpha-desc" | "created" | "created-desc" | "price-asc" | "price-desc" => {
+            Some(sort_order.to_string())
+        }
+        "best-selling" => Some("created-desc".to_string()),
+        _ => None,
+    }
 }
 
 impl Default for ShopifyImporter {
@@ -159,7 +377,7 @@ impl PlatformImporter for ShopifyImporter {
         });
 
         // Fetch products from Shopify
-        let url = self.api_url(&shop_domain, "products");
+        let url = with_query(&self.api_url(&shop_domain, "products"), &updated_since(config));
         let shopify_products: Vec<ShopifyProduct> = self
             .fetch_paginated(&url, &access_token, config.options.limit)
             .await?;
@@ -213,6 +431,12 @@ impl PlatformImporter for ShopifyImporter {
             }
         }
 
+        if !dry_run {
+            self.import_images(&shopify_products, config, progress).await?;
+        }
+        self.import_collections(&shop_domain, &access_token, &shopify_products, config, progress, &mut stats)
+            .await?;
+
         Ok(stats)
     }
 
@@ -246,7 +470,7 @@ impl PlatformImporter for ShopifyImporter {
             },
         });
 
-        let url = self.api_url(&shop_domain, "customers");
+        let url = with_query(&self.api_url(&shop_domain, "customers"), &updated_since(config));
         let shopify_customers: Vec<ShopifyCustomer> = self
             .fetch_paginated(&url, &access_token, config.options.limit)
             .await?;
@@ -319,7 +543,7 @@ impl PlatformImporter for ShopifyImporter {
             },
         });
 
-        let url = self.api_url(&shop_domain, "orders");
+        let url = with_query(&self.api_url(&shop_domain, "orders"), &updated_since(config));
         let shopify_orders: Vec<ShopifyOrder> = self
             .fetch_paginated(&url, &access_token, config.options.limit)
             .await?;
@@ -382,6 +606,56 @@ struct ShopifyProduct {
     images: Vec<ShopifyImage>,
 }
 
+/// A custom (manual) or smart collection
+#[derive(Debug, Deserialize)]
+struct ShopifyCollection {
+    id: u64,
+    title: String,
+    handle: String,
+    body_html: Option<String>,
+    #[serde(default)]
+    sort_order: String,
+    published_at: Option<String>,
+    /// Smart collections only
+    #[serde(default)]
+    rules: Vec<ShopifyCollectionRule>,
+    #[serde(default)]
+    disjunctive: bool,
+}
+
+impl ShopifyCollection {
+    fn to_request(&self, collection_type: CollectionType, rules: Vec<CollectionRule>) -> CreateCollectionRequest {
+        CreateCollectionRequest {
+            title: self.title.clone(),
+            handle: Some(self.handle.clone()),
+            description: self.body_html.clone().filter(|body| !body.is_empty()),
+            seo_title: None,
+            seo_description: None,
+            collection_type,
+            rules,
+            disjunctive: self.disjunctive,
+            sort_order: collection_sort_order(&self.sort_order),
+            published: self.published_at.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ShopifyCollectionRule {
+    column: String,
+    relation: String,
+    condition: String,
+}
+
+/// A product's place in a custom collection
+#[derive(Debug, Deserialize)]
+struct ShopifyCollect {
+    collection_id: u64,
+    product_id: u64,
+    #[serde(default)]
+    position: i64,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct ShopifyVariant {
@@ -400,7 +674,7 @@ struct ShopifyVariant {
 struct ShopifyImage {
     id: u64,
     src: String,
-    #[serde(rename = "alt_text")]
+    #[serde(rename = "alt_text", alias = "alt")]
     alt_text: Option<String>,
 }
 
@@ -429,3 +703,68 @@ struct ShopifyOrder {
     #[serde(rename = "created_at")]
     created_at: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(column: &str, relation: &str, condition: &str) -> ShopifyCollectionRule {
+        ShopifyCollectionRule {
+            column: column.to_string(),
+            relation: relation.to_string(),
+            condition: condition.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_smart_collection_rules() {
+        let rules = smart_collection_rules(&[
+            rule("tag", "equals", "summer"),
+            rule("variant_price", "less_than", "25.00"),
+            rule("vendor", "contains", "Acme"),
+        ])
+        .unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                CollectionRule::new(CollectionRuleField::Tag, CollectionRuleRelation::Equals, "summer"),
+                CollectionRule::new(CollectionRuleField::Price, CollectionRuleRelation::LessThan, "25.00"),
+                CollectionRule::new(CollectionRuleField::Vendor, CollectionRuleRelation::Contains, "Acme"),
+            ]
+        );
+
+        assert!(smart_collection_rules(&[rule("title", "contains", "Shirt")]).is_err());
+        assert!(smart_collection_rules(&[rule("tag", "starts_with", "sum")]).is_err());
+        assert!(smart_collection_rules(&[rule("tag", "greater_than", "a")]).is_err());
+    }
+
+    #[test]
+    fn test_updated_since() {
+        use chrono::TimeZone;
+
+        let mut config = ImportConfig {
+            database_url: String::new(),
+            source: crate::import::types::SourceConfig::Platform {
+                platform: "shopify".to_string(),
+                api_url: "shop.myshopify.com".to_string(),
+                api_key: "token".to_string(),
+                headers: HashMap::new(),
+            },
+            options: Default::default(),
+            media: None,
+        };
+        assert_eq!(updated_since(&config), "");
+
+        config.options = config
+            .options
+            .changed_since(chrono::Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap());
+        assert_eq!(updated_since(&config), "updated_at_min=2024-05-01T10:30:00Z");
+    }
+
+    #[test]
+    fn test_collection_sort_order() {
+        assert_eq!(collection_sort_order("price-desc").as_deref(), Some("price-desc"));
+        assert_eq!(collection_sort_order("best-selling").as_deref(), Some("created-desc"));
+        assert_eq!(collection_sort_order(""), None);
+    }
+}
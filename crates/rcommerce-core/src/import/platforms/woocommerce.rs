@@ -2,20 +2,24 @@
 
 use crate::import::{
     error::{ImportError, ImportResult},
+    platforms::ImageImporter,
+    sync::with_query,
     types::{ImportConfig, ImportProgress, ImportStats},
     PlatformImporter,
 };
 use crate::models::{
-    CreateProductRequest, UpdateProductRequest, Currency, ProductType,
+    CreateProductRequest, UpdateProductRequest, Currency, ProductType, ProductCategory,
     InventoryPolicy, WeightUnit, OrderStatus, PaymentStatus, FulfillmentStatus,
 };
-use crate::repository::{ProductRepository, Database};
+use crate::repository::{CategoryRepository, PostgresCategoryRepository, ProductRepository, Database};
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use sqlx::PgPool;
+use std::collections::HashMap;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -89,7 +93,7 @@ impl WooCommerceImporter {
         let per_page = if limit == 0 { 100 } else { 100.min(limit) };
 
         loop {
-            let request_url = format!("{}?page={}&per_page={}", url, page, per_page);
+            let request_url = with_query(url, &format!("page={}&per_page={}", page, per_page));
 
             let response = self
                 .client
@@ -168,6 +172,77 @@ impl WooCommerceImporter {
         Ok(results)
     }
 
+    /// Import product categories, reusing ours with the same slug, then
+    /// link each new root to its parent; WooCommerce category IDs mapped to ours
+    async fn import_categories(
+        &self,
+        base_url: &str,
+        consumer_key: &str,
+        consumer_secret: &str,
+        pool: &PgPool,
+        stats: &mut ImportStats,
+    ) -> ImportResult<HashMap<u64, Uuid>> {
+        let url = self.api_url(base_url, "products/categories");
+        let wc_categories: Vec<WooCommerceProductCategory> = self
+            .fetch_paginated(&url, consumer_key, consumer_secret, 0)
+            .await?;
+
+        let repo = PostgresCategoryRepository::new(pool.clone());
+        let mut category_ids = HashMap::new();
+        for wc_category in &wc_categories {
+            // WooCommerce puts every product without a category in this one
+            if wc_category.slug == "uncategorized" {
+                continue;
+            }
+            let category = match repo.get_by_slug(&wc_category.slug).await? {
+                Some(existing) => Ok(existing),
+                None => repo.create(&wc_category.to_category()).await,
+            };
+            match category {
+                Ok(category) => {
+                    category_ids.insert(wc_category.id, category.id);
+                }
+                Err(e) => {
+                    stats.errors += 1;
+                    let error_msg = format!("Failed to import category '{}': {}", wc_category.name, e);
+                    tracing::error!("{}", error_msg);
+                    stats.error_details.push(error_msg);
+                }
+            }
+        }
+
+        for wc_category in &wc_categories {
+            let (Some(&id), Some(&parent_id)) = (category_ids.get(&wc_category.id), category_ids.get(&wc_category.parent)) else {
+                continue;
+            };
+            let Some(mut category) = repo.get_by_id(id).await? else {
+                continue;
+            };
+            if category.parent_id.is_none() && id != parent_id {
+                category.parent_id = Some(parent_id);
+                repo.update(&category).await?;
+            }
+        }
+
+        tracing::info!("Imported {} WooCommerce categories", category_ids.len());
+        Ok(category_ids)
+    }
+
+    /// Put an imported product in its categories
+    async fn assign_categories(
+        &self,
+        pool: &PgPool,
+        product_id: Uuid,
+        categories: &[WooCommerceCategory],
+        category_ids: &HashMap<u64, Uuid>,
+    ) -> ImportResult<()> {
+        let repo = PostgresCategoryRepository::new(pool.clone());
+        for category_id in categories.iter().filter_map(|c| category_ids.get(&c.id)) {
+            repo.assign_product(product_id, *category_id).await?;
+        }
+        Ok(())
+    }
+
     /// Convert WooCommerce price string to Decimal
     fn parse_price(&self, price: &str) -> Decimal {
         price.parse::<Decimal>().unwrap_or(Decimal::ZERO)
@@ -253,7 +328,7 @@ impl PlatformImporter for WooCommerceImporter {
             },
         });
 
-        let url = self.api_url(&base_url, "products");
+        let url = with_query(&self.api_url(&base_url, "products"), &modified_after(config));
         tracing::info!("WooCommerce API URL: {}", url);
         
         let wc_products: Vec<WooCommerceProduct> = self
@@ -282,6 +357,25 @@ impl PlatformImporter for WooCommerceImporter {
             None
         };
 
+        // Categories first, so products can be put in them
+        let category_ids = match &pool {
+            Some(pool) => {
+                progress(ImportProgress {
+                    stage: "products".to_string(),
+                    current: 0,
+                    total: wc_products.len(),
+                    message: "Importing categories...".to_string(),
+                });
+                self.import_categories(&base_url, &consumer_key, &consumer_secret, pool, &mut stats)
+                    .await?
+            }
+            None => HashMap::new(),
+        };
+        let images = match &pool {
+            Some(pool) => ImageImporter::from_config(config, pool)?,
+            None => None,
+        };
+
         for (i, product) in wc_products.iter().enumerate() {
             progress(ImportProgress {
                 stage: "products".to_string(),
@@ -375,6 +469,7 @@ impl PlatformImporter for WooCommerceImporter {
                                 is_featured: None,
                                 seo_title: Some(Some(product.name.clone())),
                                 seo_description: Some(product.short_description.clone()),
+                                vendor: None,
                                 product_type: Some(product_type),
                                 subscription_interval: None,
                                 subscription_interval_count: None,
@@ -393,9 +488,18 @@ impl PlatformImporter for WooCommerceImporter {
                             };
 
                             match repo.update_with_request(existing_product.id, update_request).await {
-                                Ok(_) => {
+                                Ok(updated_product) => {
                                     stats.updated += 1;
                                     tracing::info!("Updated product: {}", product.name);
+                                    if let Err(e) = self
+                                        .assign_categories(pool, updated_product.id, &product.categories, &category_ids)
+                                        .await
+                                    {
+                                        tracing::warn!("Failed to categorize product '{}': {}", product.name, e);
+                                    }
+                                    if let Some(images) = &images {
+                                        images.attach(updated_product.id, product.image_sources()).await;
+                                    }
                                 }
                                 Err(e) => {
                                     stats.errors += 1;
@@ -446,6 +550,7 @@ impl PlatformImporter for WooCommerceImporter {
                             is_featured: false,
                             seo_title: Some(product.name.clone()),
                             seo_description: product.short_description.clone(),
+                            vendor: None,
                             subscription_interval: None,
                             subscription_interval_count: None,
                             subscription_trial_days: None,
@@ -460,16 +565,22 @@ impl PlatformImporter for WooCommerceImporter {
                             download_expiry_days: None,
                             bundle_pricing_strategy: None,
                             bundle_discount_percentage: None,
-                            attributes: None,
                             bundle_components: None,
                         };
 
                         match repo.create_with_request(create_request).await {
-                            Ok(_created_product) => {
+                            Ok(created_product) => {
                                 stats.created += 1;
                                 tracing::info!("Created product: {}", product.name);
-                                // TODO: Import product images if available
-                                // TODO: Import product categories if available
+                                if let Err(e) = self
+                                    .assign_categories(pool, created_product.id, &product.categories, &category_ids)
+                                    .await
+                                {
+                                    tracing::warn!("Failed to categorize product '{}': {}", product.name, e);
+                                }
+                                if let Some(images) = &images {
+                                    images.attach(created_product.id, product.image_sources()).await;
+                                }
                             }
                             Err(e) => {
                                 stats.errors += 1;
@@ -530,9 +641,13 @@ impl PlatformImporter for WooCommerceImporter {
         });
 
         let url = self.api_url(&base_url, "customers");
-        let wc_customers: Vec<WooCommerceCustomer> = self
+        let mut wc_customers: Vec<WooCommerceCustomer> = self
             .fetch_paginated(&url, &consumer_key, &consumer_secret, config.options.limit)
             .await?;
+        // The customers endpoint can't filter by modification time
+        if let Some(since) = config.options.since {
+            wc_customers.retain(|customer| customer.modified_at().map_or(true, |modified| modified > since));
+        }
 
         let mut stats = ImportStats {
             total: wc_customers.len(),
@@ -756,7 +871,7 @@ impl PlatformImporter for WooCommerceImporter {
             },
         });
 
-        let url = self.api_url(&base_url, "orders");
+        let url = with_query(&self.api_url(&base_url, "orders"), &modified_after(config));
         let wc_orders: Vec<WooCommerceOrder> = self
             .fetch_paginated(&url, &consumer_key, &consumer_secret, config.options.limit)
             .await?;
@@ -1320,6 +1435,15 @@ impl WooCommerceImporter {
     }
 }
 
+/// Filter for records modified after `options.since` (delta syncs)
+fn modified_after(config: &ImportConfig) -> String {
+    config
+        .options
+        .since
+        .map(|since| format!("modified_after={}&dates_are_gmt=true", since.format("%Y-%m-%dT%H:%M:%S")))
+        .unwrap_or_default()
+}
+
 // WooCommerce API response types
 // These are used for API deserialization - fields are read by serde
 #[allow(dead_code)]
@@ -1357,6 +1481,61 @@ struct WooCommerceCategory {
     slug: String,
 }
 
+/// A category from `products/categories`
+#[derive(Debug, Deserialize)]
+struct WooCommerceProductCategory {
+    id: u64,
+    name: String,
+    slug: String,
+    /// 0 for top-level categories
+    #[serde(default)]
+    parent: u64,
+    #[serde(default)]
+    description: String,
+    image: Option<WooCommerceImage>,
+    #[serde(default)]
+    menu_order: i32,
+}
+
+impl WooCommerceProduct {
+    /// Image URLs with their alt text, gallery order
+    fn image_sources(&self) -> Vec<(String, Option<String>)> {
+        self.images
+            .iter()
+            .filter(|image| !image.src.is_empty())
+            .map(|image| (image.src.clone(), image.alt.as_deref().map(decode_entities)))
+            .collect()
+    }
+}
+
+impl WooCommerceProductCategory {
+    /// A new root category (parents are linked once all exist)
+    fn to_category(&self) -> ProductCategory {
+        let now = chrono::Utc::now();
+        ProductCategory {
+            id: Uuid::new_v4(),
+            name: decode_entities(&self.name),
+            slug: self.slug.clone(),
+            description: Some(self.description.clone()).filter(|d| !d.is_empty()),
+            parent_id: None,
+            image_url: self.image.as_ref().map(|image| image.src.clone()),
+            sort_order: self.menu_order,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Undo the HTML escaping WordPress applies to term names
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&amp;", "&")
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct WooCommerceImage {
@@ -1386,6 +1565,18 @@ struct WooCommerceCustomer {
     username: String,
     billing: Option<WooCommerceAddress>,
     shipping: Option<WooCommerceAddress>,
+    #[serde(default)]
+    date_modified_gmt: Option<String>,
+}
+
+impl WooCommerceCustomer {
+    /// When the customer last changed; WooCommerce gives GMT without an offset
+    fn modified_at(&self) -> Option<DateTime<Utc>> {
+        let modified = self.date_modified_gmt.as_deref()?;
+        chrono::NaiveDateTime::parse_from_str(modified, "%Y-%m-%dT%H:%M:%S")
+            .ok()
+            .map(|time| time.and_utc())
+    }
 }
 
 #[allow(dead_code)]
@@ -1438,3 +1629,36 @@ struct WooCommerceLineItem {
     total: String,
     sku: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_customer_modified_at() {
+        use chrono::TimeZone;
+
+        let customer: WooCommerceCustomer = serde_json::from_str(
+            r#"{"id": 7, "email": "a@example.com", "first_name": "A", "last_name": "B", "username": "ab",
+                "billing": null, "shipping": null, "date_modified_gmt": "2024-05-01T10:30:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(customer.modified_at(), Some(Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_category_from_woocommerce() {
+        let wc_category: WooCommerceProductCategory = serde_json::from_str(
+            r#"{"id": 15, "name": "Tops &amp; Tees", "slug": "tops-tees", "parent": 9,
+                "description": "", "image": null, "menu_order": 2, "count": 4}"#,
+        )
+        .unwrap();
+        let category = wc_category.to_category();
+
+        assert_eq!(category.name, "Tops & Tees");
+        assert_eq!(category.slug, "tops-tees");
+        assert_eq!(category.description, None);
+        assert_eq!(category.parent_id, None);
+        assert_eq!(category.sort_order, 2);
+    }
+}
@@ -0,0 +1,118 @@
+//! Sync cursors for incremental platform imports
+//!
+//! A cursor is the time a platform import last completed without errors,
+//! per store (API URL) and entity type. Delta syncs fetch only records
+//! changed after it, so nightly runs don't re-scan the whole catalog.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::import::{error::ImportResult, EntityType, ImportError};
+
+/// Sync cursors stored in `import_sync_cursors`
+pub struct SyncCursors {
+    pool: PgPool,
+}
+
+impl SyncCursors {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Connect to the database imports write to
+    pub async fn connect(database_url: &str) -> ImportResult<Self> {
+        use sqlx::postgres::PgPoolOptions;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(database_url)
+            .await
+            .map_err(|e| ImportError::Database(crate::Error::Database(e)))?;
+        Ok(Self::new(pool))
+    }
+
+    /// When `entity` was last synced from the store, if ever
+    pub async fn get(&self, platform: &str, source: &str, entity: EntityType) -> ImportResult<Option<DateTime<Utc>>> {
+        sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT synced_at FROM import_sync_cursors WHERE platform = $1 AND source = $2 AND entity = $3",
+        )
+        .bind(platform)
+        .bind(source)
+        .bind(entity.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ImportError::Database(crate::Error::Database(e)))
+    }
+
+    /// Record a sync of `entity` covering changes up to `synced_at`
+    pub async fn save(
+        &self,
+        platform: &str,
+        source: &str,
+        entity: EntityType,
+        synced_at: DateTime<Utc>,
+    ) -> ImportResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO import_sync_cursors (platform, source, entity, synced_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (platform, source, entity) DO UPDATE
+            SET synced_at = EXCLUDED.synced_at, updated_at = NOW()
+            "#,
+        )
+        .bind(platform)
+        .bind(source)
+        .bind(entity.to_string())
+        .bind(synced_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ImportError::Database(crate::Error::Database(e)))?;
+        Ok(())
+    }
+}
+
+/// Parse a `--since` value: an RFC 3339 time or a date (midnight UTC)
+pub fn parse_since(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+/// Append query parameters to a URL that may already have some
+pub(crate) fn with_query(url: &str, query: &str) -> String {
+    if query.is_empty() {
+        url.to_string()
+    } else if url.contains('?') {
+        format!("{}&{}", url, query)
+    } else {
+        format!("{}?{}", url, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(
+            parse_since("2024-05-01T12:30:00+02:00"),
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap())
+        );
+        assert_eq!(parse_since("2024-05-01"), Some(Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()));
+        assert_eq!(parse_since("yesterday"), None);
+    }
+
+    #[test]
+    fn test_with_query() {
+        assert_eq!(with_query("https://shop/a.json", ""), "https://shop/a.json");
+        assert_eq!(with_query("https://shop/a.json", "x=1"), "https://shop/a.json?x=1");
+        assert_eq!(with_query("https://shop/a.json?x=1", "y=2"), "https://shop/a.json?x=1&y=2");
+    }
+}
@@ -1,9 +1,12 @@
 //! Import types and configuration
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::config::MediaConfig;
+
 /// Configuration for import operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportConfig {
@@ -16,6 +19,11 @@ pub struct ImportConfig {
 
     /// Import options
     pub options: ImportOptions,
+
+    /// Media storage product images are downloaded into; without it
+    /// images are not imported
+    #[serde(default)]
+    pub media: Option<MediaConfig>,
 }
 
 /// Source-specific configuration
@@ -85,6 +93,21 @@ pub struct ImportOptions {
     /// Default currency for imported records (e.g., "USD", "AUD", "EUR")
     #[serde(default = "default_currency")]
     pub default_currency: String,
+
+    /// Only fetch records changed after this time (delta sync)
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl ImportOptions {
+    /// A delta sync of records changed after `since`; records that exist
+    /// already are updated rather than skipped
+    pub fn changed_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self.skip_existing = false;
+        self.update_existing = true;
+        self
+    }
 }
 
 impl Default for ImportOptions {
@@ -100,6 +123,7 @@ impl Default for ImportOptions {
             default_values: HashMap::new(),
             transforms: Vec::new(),
             default_currency: default_currency(),
+            since: None,
         }
     }
 }
@@ -0,0 +1,316 @@
+//! Stock adjustment approvals
+//!
+//! Manual stock corrections are made through adjustment requests. Changes
+//! below the configured thresholds (`[stock_adjustments]`) are posted
+//! straight away; larger ones wait until a second staff member with an
+//! approver role approves them, and approvers are notified. Approved
+//! adjustments are posted as `adjustment` stock movements against the
+//! stock level at the time of approval. Each step is recorded in the
+//! adjustment's audit trail.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::StockAdjustmentConfig;
+use crate::models::CustomerRole;
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, StockAdjustmentRepository};
+use crate::{Error, Result};
+
+/// Stock adjustment status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "stock_adjustment_status", rename_all = "snake_case")]
+pub enum StockAdjustmentStatus {
+    /// Waiting for approval
+    Pending,
+    /// Approved (or below the thresholds) and posted as a stock movement
+    Approved,
+    Rejected,
+    Cancelled,
+}
+
+/// Stock adjustment request
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockAdjustmentRequest {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    pub quantity_change: i32,
+    /// Available quantity when the adjustment was requested
+    pub quantity_at_request: i32,
+    /// Available quantity before and after posting
+    pub quantity_before: Option<i32>,
+    pub quantity_after: Option<i32>,
+    pub reason: String,
+    pub notes: Option<String>,
+    pub status: StockAdjustmentStatus,
+    pub requires_approval: bool,
+    pub requested_by: Uuid,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_notes: Option<String>,
+    pub stock_movement_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Audit trail entry for a stock adjustment
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockAdjustmentAuditEntry {
+    pub id: Uuid,
+    pub adjustment_id: Uuid,
+    /// requested, approved, rejected, cancelled or posted
+    pub action: String,
+    /// None for automatic steps
+    pub actor_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request a stock adjustment
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateStockAdjustmentRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub location_id: Uuid,
+    /// Units to add (positive) or remove (negative)
+    #[validate(range(min = -1_000_000, max = 1_000_000))]
+    pub quantity_change: i32,
+    #[validate(length(min = 1, max = 255))]
+    pub reason: String,
+    pub notes: Option<String>,
+}
+
+/// Approve, reject or cancel an adjustment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StockAdjustmentDecision {
+    pub notes: Option<String>,
+}
+
+/// Stock adjustment service
+pub struct StockAdjustmentService<R: StockAdjustmentRepository> {
+    repository: R,
+    config: StockAdjustmentConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+}
+
+impl<R: StockAdjustmentRepository> StockAdjustmentService<R> {
+    pub fn new(repository: R, config: StockAdjustmentConfig) -> Self {
+        Self {
+            repository,
+            config,
+            notifications: None,
+        }
+    }
+
+    /// Queue pending-approval notifications (without it, pending
+    /// adjustments are only listed)
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &StockAdjustmentConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Whether a change needs a second approval at the current stock level
+    pub fn requires_approval(&self, quantity_change: i32, current_quantity: i32) -> bool {
+        let units = quantity_change.unsigned_abs();
+
+        let over_units = self.config.approval_threshold_units.is_some_and(|threshold| units >= threshold);
+        let over_percent = self.config.approval_threshold_percent.is_some_and(|percent| {
+            // Any change to an empty level is a 100% change
+            current_quantity <= 0 || u64::from(units) * 100 >= u64::from(percent) * current_quantity as u64
+        });
+
+        over_units || over_percent
+    }
+
+    /// Request an adjustment; it is posted immediately unless it needs approval
+    pub async fn request(&self, actor_id: Uuid, request: CreateStockAdjustmentRequest) -> Result<StockAdjustmentRequest> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.quantity_change == 0 {
+            return Err(Error::validation("quantity_change must not be zero"));
+        }
+
+        let role = self.actor_role(actor_id).await?;
+        if role == CustomerRole::Customer {
+            return Err(forbidden("Only staff can adjust stock"));
+        }
+
+        let current = self
+            .repository
+            .current_quantity(request.product_id, request.variant_id, request.location_id)
+            .await?
+            .ok_or_else(|| Error::not_found("No inventory level for this product at this location"))?;
+        if current + request.quantity_change < 0 {
+            return Err(Error::validation(format!(
+                "Adjustment would leave negative stock ({} available)",
+                current
+            )));
+        }
+
+        let requires_approval = self.requires_approval(request.quantity_change, current);
+        let adjustment = self.repository.create(&request, actor_id, current, requires_approval).await?;
+
+        if !requires_approval {
+            return self.repository.post(adjustment.id, None, Some("Below approval thresholds")).await;
+        }
+
+        tracing::info!(
+            "Stock adjustment {} ({:+} units of product {}) is awaiting approval",
+            adjustment.id, adjustment.quantity_change, adjustment.product_id
+        );
+        if let Err(e) = self.notify_approvers(&adjustment).await {
+            tracing::warn!("Failed to notify approvers of stock adjustment {}: {}", adjustment.id, e);
+        }
+        Ok(adjustment)
+    }
+
+    /// Approve a pending adjustment and post it as a stock movement
+    pub async fn approve(&self, actor_id: Uuid, id: Uuid, decision: StockAdjustmentDecision) -> Result<StockAdjustmentRequest> {
+        let adjustment = self.pending(id).await?;
+        self.check_approver(actor_id, &adjustment).await?;
+
+        self.repository.post(id, Some(actor_id), decision.notes.as_deref()).await
+    }
+
+    /// Reject a pending adjustment
+    pub async fn reject(&self, actor_id: Uuid, id: Uuid, decision: StockAdjustmentDecision) -> Result<StockAdjustmentRequest> {
+        let adjustment = self.pending(id).await?;
+        self.check_approver(actor_id, &adjustment).await?;
+
+        self.close(id, StockAdjustmentStatus::Rejected, actor_id, decision.notes.as_deref()).await
+    }
+
+    /// Withdraw a pending adjustment (requester or an approver)
+    pub async fn cancel(&self, actor_id: Uuid, id: Uuid, decision: StockAdjustmentDecision) -> Result<StockAdjustmentRequest> {
+        let adjustment = self.pending(id).await?;
+        if adjustment.requested_by != actor_id {
+            let role = self.actor_role(actor_id).await?;
+            if !self.config.approver_roles.contains(&role) {
+                return Err(forbidden("Only the requester or an approver can cancel this adjustment"));
+            }
+        }
+
+        self.close(id, StockAdjustmentStatus::Cancelled, actor_id, decision.notes.as_deref()).await
+    }
+
+    /// Get an adjustment
+    pub async fn get(&self, id: Uuid) -> Result<StockAdjustmentRequest> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Stock adjustment not found"))
+    }
+
+    async fn close(
+        &self,
+        id: Uuid,
+        status: StockAdjustmentStatus,
+        actor_id: Uuid,
+        notes: Option<&str>,
+    ) -> Result<StockAdjustmentRequest> {
+        self.repository
+            .close(id, status, actor_id, notes)
+            .await?
+            .ok_or_else(|| Error::validation("Stock adjustment is no longer pending"))
+    }
+
+    async fn pending(&self, id: Uuid) -> Result<StockAdjustmentRequest> {
+        let adjustment = self.get(id).await?;
+        if adjustment.status != StockAdjustmentStatus::Pending {
+            return Err(Error::validation("Stock adjustment is no longer pending"));
+        }
+        Ok(adjustment)
+    }
+
+    async fn check_approver(&self, actor_id: Uuid, adjustment: &StockAdjustmentRequest) -> Result<()> {
+        let role = self.actor_role(actor_id).await?;
+        if !self.config.approver_roles.contains(&role) {
+            return Err(forbidden("Your role cannot approve stock adjustments"));
+        }
+        if adjustment.requested_by == actor_id && !self.config.allow_self_approval {
+            return Err(forbidden("Adjustments must be approved by someone other than the requester"));
+        }
+        Ok(())
+    }
+
+    async fn actor_role(&self, actor_id: Uuid) -> Result<CustomerRole> {
+        self.repository
+            .actor_role(actor_id)
+            .await?
+            .ok_or_else(|| Error::unauthorized("Unknown user"))
+    }
+
+    async fn notify_approvers(&self, adjustment: &StockAdjustmentRequest) -> Result<()> {
+        let Some(ref notifications) = self.notifications else {
+            return Ok(());
+        };
+        if !self.config.notify_approvers {
+            return Ok(());
+        }
+
+        let approvers = self.repository.find_approvers(&self.config.approver_roles).await?;
+        for (approver_id, email) in approvers {
+            if approver_id == adjustment.requested_by && !self.config.allow_self_approval {
+                continue;
+            }
+            let notification = NotificationFactory::stock_adjustment_pending(adjustment, Recipient::email(email, None));
+            notifications.create(&notification).await?;
+        }
+        Ok(())
+    }
+}
+
+fn forbidden(message: &str) -> Error {
+    Error::HttpError(http::StatusCode::FORBIDDEN, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::PostgresStockAdjustmentRepository;
+
+    fn service(config: StockAdjustmentConfig) -> StockAdjustmentService<PostgresStockAdjustmentRepository> {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/rcommerce").unwrap();
+        StockAdjustmentService::new(PostgresStockAdjustmentRepository::new(pool), config)
+    }
+
+    #[tokio::test]
+    async fn test_requires_approval_thresholds() {
+        // Defaults: 50 units or 25% of current stock
+        let adjustments = service(StockAdjustmentConfig::default());
+        assert!(!adjustments.requires_approval(-5, 100));
+        assert!(adjustments.requires_approval(-25, 100));
+        assert!(adjustments.requires_approval(60, 1_000));
+        assert!(!adjustments.requires_approval(40, 1_000));
+        assert!(adjustments.requires_approval(1, 0));
+
+        let units_only = service(StockAdjustmentConfig {
+            approval_threshold_percent: None,
+            ..Default::default()
+        });
+        assert!(!units_only.requires_approval(-25, 100));
+        assert!(units_only.requires_approval(-50, 100));
+
+        let disabled = service(StockAdjustmentConfig {
+            approval_threshold_units: None,
+            approval_threshold_percent: None,
+            ..Default::default()
+        });
+        assert!(!disabled.requires_approval(10_000, 0));
+    }
+}
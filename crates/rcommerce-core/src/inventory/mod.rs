@@ -2,6 +2,10 @@ pub mod service;
 pub mod reservation;
 pub mod tracking;
 pub mod notification;
+pub mod approval;
+pub mod purchasing;
+pub mod supplier_feed;
+pub mod po_dispatch;
 
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
@@ -11,17 +15,39 @@ pub use service::{InventoryService, StockAlertLevel};
 pub use reservation::{StockReservation, ReservationStatus};
 pub use tracking::{InventoryLevel, StockMovement, StockStatus};
 pub use notification::LowStockAlert;
+pub use approval::{
+    CreateStockAdjustmentRequest, StockAdjustmentAuditEntry, StockAdjustmentDecision, StockAdjustmentRequest,
+    StockAdjustmentService, StockAdjustmentStatus,
+};
+pub use purchasing::{
+    CreatePurchaseOrderRequest, CreateSupplierRequest, LowStockLevel, PurchaseOrder, PurchaseOrderDetail,
+    PurchaseOrderItem, PurchaseOrderItemInput, PurchaseOrderStatus, PurchasingService, ReceivePurchaseOrderRequest,
+    ReceivedItem, ReorderJob, ReorderReport, Supplier, SupplierProduct, SupplierProductRequest,
+    UpdatePurchaseOrderRequest, UpdateSupplierRequest,
+};
+pub use supplier_feed::{
+    DropshipJob, DropshipOrder, DropshipOrderDetail, DropshipOrderItem, DropshipOrderPayload, DropshipOrderPayloadItem,
+    DropshipReport, DropshipStatus, FeedFormat, FeedMapping, FeedRunReport, FeedSyncReport, MarginRule,
+    SaveSupplierFeedRequest, SupplierFeed, SupplierFeedJob, SupplierFeedService,
+};
+pub use po_dispatch::{
+    AcknowledgePurchaseOrderRequest, AcknowledgedItem, Discrepancy, DiscrepancyKind, DiscrepancySource,
+    DispatchReport, PoDispatchMethod, PoDispatchStatus, PurchaseOrderDispatchJob, PurchaseOrderDispatchService,
+    PurchaseOrderEvent, PurchaseOrderEventType, PurchaseOrderTracking, ResolveDiscrepancyRequest,
+    SaveDispatchSettingsRequest, SupplierDispatchSettings,
+};
 
 /// Inventory configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct InventoryConfig {
     /// Low stock threshold (percentage)
     pub low_stock_threshold: u32,
     /// Enable automatic restocking alerts
     pub enable_restock_alerts: bool,
-    /// Enable stock reservations
+    /// Reserve stock for new orders, refusing orders that would oversell
     pub enable_reservations: bool,
-    /// Reservation timeout (minutes)
+    /// Unpaid orders hold their stock this long (minutes)
     pub reservation_timeout_minutes: u32,
 }
 
@@ -0,0 +1,879 @@
+//! Purchase order dispatch, acknowledgments and receipt matching
+//!
+//! Placing a purchase order queues it for the `po_dispatch` job, which
+//! sends it the way the supplier's dispatch settings say: emailed with a
+//! link to acknowledge it, uploaded over SFTP as an EDI-lite CSV, or left
+//! to staff (`manual`). Suppliers without settings are emailed. Failed
+//! sends are retried up to `max_dispatch_attempts` times.
+//!
+//! An acknowledgment (from the supplier's link, or recorded by staff)
+//! confirms line quantities and costs, the supplier's reference and an
+//! ETA. Confirmed quantities and costs that differ from the order, and
+//! deliveries whose invoiced quantities or costs differ from what was
+//! received or ordered, become discrepancies the purchasing team
+//! (`alert_roles`) is alerted about and resolves. The job also alerts them
+//! about orders unacknowledged after `ack_overdue_hours`, and about orders
+//! past their expected date (once per ETA).
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use super::purchasing::{PurchaseOrder, PurchaseOrderDetail, PurchaseOrderItem, ReceivedItem};
+use crate::config::PurchasingConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, PoDispatchRepository};
+use crate::{Error, Result};
+
+/// Discrepancies listed at most
+pub const MAX_LIST_LIMIT: i64 = 200;
+
+/// How purchase orders reach a supplier
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "po_dispatch_method", rename_all = "snake_case")]
+pub enum PoDispatchMethod {
+    /// With a link to acknowledge the order
+    #[default]
+    Email,
+    /// EDI-lite CSV uploaded to `sftp_target`
+    Sftp,
+    /// Staff send orders themselves
+    Manual,
+}
+
+/// Sending a placed order to its supplier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "po_dispatch_status", rename_all = "snake_case")]
+pub enum PoDispatchStatus {
+    Pending,
+    Sent,
+    /// Retried until `max_dispatch_attempts`
+    Failed,
+}
+
+/// What happened to a placed order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "po_event_type", rename_all = "snake_case")]
+pub enum PurchaseOrderEventType {
+    Dispatched,
+    DispatchFailed,
+    Acknowledged,
+    /// The expected date changed (`details.from`, `details.to`)
+    EtaChanged,
+    Discrepancy,
+    /// Staff were alerted that the supplier has not acknowledged it
+    AckOverdue,
+    /// Staff were alerted that it is past its expected date
+    Overdue,
+}
+
+impl PurchaseOrderEventType {
+    pub const ALL: [PurchaseOrderEventType; 7] = [
+        PurchaseOrderEventType::Dispatched,
+        PurchaseOrderEventType::DispatchFailed,
+        PurchaseOrderEventType::Acknowledged,
+        PurchaseOrderEventType::EtaChanged,
+        PurchaseOrderEventType::Discrepancy,
+        PurchaseOrderEventType::AckOverdue,
+        PurchaseOrderEventType::Overdue,
+    ];
+
+    /// Event name as stored in `purchase_order_events.event`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PurchaseOrderEventType::Dispatched => "dispatched",
+            PurchaseOrderEventType::DispatchFailed => "dispatch_failed",
+            PurchaseOrderEventType::Acknowledged => "acknowledged",
+            PurchaseOrderEventType::EtaChanged => "eta_changed",
+            PurchaseOrderEventType::Discrepancy => "discrepancy",
+            PurchaseOrderEventType::AckOverdue => "ack_overdue",
+            PurchaseOrderEventType::Overdue => "overdue",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PurchaseOrderEventType::Dispatched => "The order was sent to the supplier (`details.method`)",
+            PurchaseOrderEventType::DispatchFailed => "Sending the order failed (`details.error`)",
+            PurchaseOrderEventType::Acknowledged => {
+                "The supplier acknowledged the order (`details.supplier_reference`, `details.expected_at`)"
+            }
+            PurchaseOrderEventType::EtaChanged => "The expected date changed (`details.from`, `details.to`)",
+            PurchaseOrderEventType::Discrepancy => "Discrepancies were recorded (`details.discrepancy_ids`)",
+            PurchaseOrderEventType::AckOverdue => "Staff were alerted that the supplier has not acknowledged it",
+            PurchaseOrderEventType::Overdue => "Staff were alerted that it is past its expected date",
+        }
+    }
+}
+
+/// What differs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "po_discrepancy_kind", rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    Quantity,
+    Price,
+}
+
+/// Where a discrepancy was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "po_discrepancy_source", rename_all = "snake_case")]
+pub enum DiscrepancySource {
+    Acknowledgment,
+    Receipt,
+}
+
+/// A supplier's dispatch settings
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SupplierDispatchSettings {
+    pub supplier_id: Uuid,
+    pub method: PoDispatchMethod,
+    /// Defaults to the supplier's email
+    pub email: Option<String>,
+    /// `user@host:/directory`
+    pub sftp_target: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Set how purchase orders reach a supplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveDispatchSettingsRequest {
+    pub method: PoDispatchMethod,
+    pub email: Option<String>,
+    pub sftp_target: Option<String>,
+}
+
+impl SaveDispatchSettingsRequest {
+    pub fn validate(&self) -> Result<()> {
+        if self.email.as_deref().is_some_and(|email| !email.contains('@')) {
+            return Err(Error::validation("email is not an email address"));
+        }
+        match (self.method, self.sftp_target.as_deref()) {
+            (PoDispatchMethod::Sftp, None) => Err(Error::validation("SFTP dispatch needs an sftp_target")),
+            (_, Some(target)) => parse_sftp_target(target).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Split `user@host:/directory` into the host and the directory
+pub fn parse_sftp_target(target: &str) -> Result<(&str, &str)> {
+    let (host, directory) = target.split_once(':').unwrap_or((target, ""));
+    if host.is_empty() || host.starts_with('-') || host.contains(char::is_whitespace) {
+        return Err(Error::validation("sftp_target must look like user@host:/directory"));
+    }
+    if directory.contains(['"', '\n', '\r']) {
+        return Err(Error::validation("sftp_target directory contains invalid characters"));
+    }
+    Ok((host, directory.trim_end_matches('/')))
+}
+
+/// Something that happened to a placed order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct PurchaseOrderEvent {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub event: PurchaseOrderEventType,
+    pub details: serde_json::Value,
+    pub actor_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A confirmed or delivered line that differs from the order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Discrepancy {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub item_id: Uuid,
+    pub source: DiscrepancySource,
+    pub kind: DiscrepancyKind,
+    /// Units for quantities, unit costs for prices
+    pub expected: Decimal,
+    pub actual: Decimal,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    pub resolution: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A discrepancy to record
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewDiscrepancy {
+    pub item_id: Uuid,
+    pub source: DiscrepancySource,
+    pub kind: DiscrepancyKind,
+    pub expected: Decimal,
+    pub actual: Decimal,
+}
+
+/// A line as the supplier confirms it; omitted values match the order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcknowledgedItem {
+    pub item_id: Uuid,
+    pub quantity: Option<i32>,
+    pub unit_cost: Option<Decimal>,
+}
+
+/// A supplier's acknowledgment of a purchase order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AcknowledgePurchaseOrderRequest {
+    pub supplier_reference: Option<String>,
+    /// The supplier's ETA
+    pub expected_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub items: Vec<AcknowledgedItem>,
+}
+
+/// Resolve a discrepancy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveDiscrepancyRequest {
+    /// What was agreed, e.g. "credit note requested"
+    pub resolution: String,
+}
+
+/// A purchase order with its dispatch history and discrepancies
+#[derive(Debug, Clone, Serialize)]
+pub struct PurchaseOrderTracking {
+    #[serde(flatten)]
+    pub detail: PurchaseOrderDetail,
+    pub dispatch_attempts: i32,
+    pub dispatch_error: Option<String>,
+    pub events: Vec<PurchaseOrderEvent>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Outcome of a run of the `po_dispatch` job
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DispatchReport {
+    pub sent: usize,
+    pub failed: usize,
+    /// Orders staff were alerted are unacknowledged
+    pub ack_overdue: usize,
+    /// Orders staff were alerted are late
+    pub overdue: usize,
+}
+
+impl std::fmt::Display for DispatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} purchase orders sent, {} failed", self.sent, self.failed)?;
+        if self.ack_overdue > 0 || self.overdue > 0 {
+            write!(f, ", {} unacknowledged and {} late alerted", self.ack_overdue, self.overdue)?;
+        }
+        Ok(())
+    }
+}
+
+/// The EDI-lite CSV of a purchase order: one row per line
+pub fn edi_csv(detail: &PurchaseOrderDetail) -> Result<String> {
+    let order = &detail.purchase_order;
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| Error::Other(format!("Failed to write purchase order CSV: {}", e));
+    writer
+        .write_record([
+            "po_number", "line", "supplier_sku", "product_id", "variant_id", "quantity", "unit_cost", "currency",
+            "expected_date",
+        ])
+        .map_err(csv_error)?;
+    let expected_date = order.expected_at.map(|at| at.date_naive().to_string()).unwrap_or_default();
+    for (index, item) in detail.items.iter().enumerate() {
+        writer
+            .write_record([
+                order.po_number.clone(),
+                (index + 1).to_string(),
+                item.supplier_sku.clone().unwrap_or_default(),
+                item.product_id.to_string(),
+                item.variant_id.map(|id| id.to_string()).unwrap_or_default(),
+                item.quantity_ordered.to_string(),
+                item.unit_cost.map(|cost| cost.to_string()).unwrap_or_default(),
+                order.currency.clone(),
+                expected_date.clone(),
+            ])
+            .map_err(csv_error)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| Error::Other(format!("Failed to write purchase order CSV: {}", e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Whether `actual` is further from `expected` than the tolerance allows
+fn price_differs(expected: Decimal, actual: Decimal, tolerance_percent: Decimal) -> bool {
+    (actual - expected).abs() > expected.abs() * tolerance_percent / Decimal::from(100)
+}
+
+/// Confirmed quantities and costs that differ from the order; every line
+/// must be on it
+pub fn acknowledgment_discrepancies(
+    items: &[PurchaseOrderItem],
+    acknowledged: &[AcknowledgedItem],
+    tolerance_percent: Decimal,
+) -> Result<Vec<NewDiscrepancy>> {
+    let by_id: HashMap<Uuid, &PurchaseOrderItem> = items.iter().map(|item| (item.id, item)).collect();
+    let mut found = Vec::new();
+    for confirmed in acknowledged {
+        let item = by_id
+            .get(&confirmed.item_id)
+            .ok_or_else(|| Error::validation(format!("Line {} is not on this purchase order", confirmed.item_id)))?;
+        if confirmed.quantity.is_some_and(|quantity| quantity < 0)
+            || confirmed.unit_cost.is_some_and(|cost| cost.is_sign_negative())
+        {
+            return Err(Error::validation("Confirmed quantities and costs must not be negative"));
+        }
+        if let Some(quantity) = confirmed.quantity.filter(|quantity| *quantity != item.quantity_ordered) {
+            found.push(NewDiscrepancy {
+                item_id: item.id,
+                source: DiscrepancySource::Acknowledgment,
+                kind: DiscrepancyKind::Quantity,
+                expected: Decimal::from(item.quantity_ordered),
+                actual: Decimal::from(quantity),
+            });
+        }
+        if let (Some(expected), Some(actual)) = (item.unit_cost, confirmed.unit_cost) {
+            if price_differs(expected, actual, tolerance_percent) {
+                found.push(NewDiscrepancy {
+                    item_id: item.id,
+                    source: DiscrepancySource::Acknowledgment,
+                    kind: DiscrepancyKind::Price,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Deliveries whose invoiced quantity differs from what was received, or
+/// whose invoiced cost differs from the confirmed (or ordered) cost
+pub fn receipt_discrepancies(
+    items: &[PurchaseOrderItem],
+    received: &[ReceivedItem],
+    tolerance_percent: Decimal,
+) -> Vec<NewDiscrepancy> {
+    let by_id: HashMap<Uuid, &PurchaseOrderItem> = items.iter().map(|item| (item.id, item)).collect();
+    let mut found = Vec::new();
+    for delivery in received {
+        let Some(item) = by_id.get(&delivery.item_id) else {
+            continue;
+        };
+        if let Some(invoiced) = delivery.invoiced_quantity.filter(|invoiced| *invoiced != delivery.quantity) {
+            found.push(NewDiscrepancy {
+                item_id: item.id,
+                source: DiscrepancySource::Receipt,
+                kind: DiscrepancyKind::Quantity,
+                expected: Decimal::from(invoiced),
+                actual: Decimal::from(delivery.quantity),
+            });
+        }
+        if let (Some(expected), Some(actual)) = (item.confirmed_unit_cost.or(item.unit_cost), delivery.invoiced_unit_cost) {
+            if price_differs(expected, actual, tolerance_percent) {
+                found.push(NewDiscrepancy {
+                    item_id: item.id,
+                    source: DiscrepancySource::Receipt,
+                    kind: DiscrepancyKind::Price,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Purchase order dispatch, acknowledgment and receipt matching service
+pub struct PurchaseOrderDispatchService<R: PoDispatchRepository> {
+    repository: R,
+    config: PurchasingConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+}
+
+impl<R: PoDispatchRepository> PurchaseOrderDispatchService<R> {
+    pub fn new(repository: R, config: PurchasingConfig) -> Self {
+        Self {
+            repository,
+            config,
+            notifications: None,
+        }
+    }
+
+    /// Queue emailed orders and staff alerts (without it, emailed orders
+    /// fail to send and alerts are only logged)
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &PurchasingConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// A supplier's dispatch settings; suppliers without any are emailed
+    pub async fn settings(&self, supplier_id: Uuid) -> Result<SupplierDispatchSettings> {
+        self.repository
+            .find_supplier(supplier_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))?;
+        Ok(self
+            .repository
+            .find_settings(supplier_id)
+            .await?
+            .unwrap_or_else(|| SupplierDispatchSettings {
+                supplier_id,
+                method: PoDispatchMethod::Email,
+                email: None,
+                sftp_target: None,
+                updated_at: Utc::now(),
+            }))
+    }
+
+    /// Set how purchase orders reach a supplier
+    pub async fn save_settings(&self, supplier_id: Uuid, request: SaveDispatchSettingsRequest) -> Result<SupplierDispatchSettings> {
+        request.validate()?;
+        self.repository
+            .find_supplier(supplier_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))?;
+        self.repository.save_settings(supplier_id, &request).await
+    }
+
+    /// A purchase order with its events and discrepancies
+    pub async fn tracking(&self, id: Uuid) -> Result<PurchaseOrderTracking> {
+        let (purchase_order, dispatch_attempts, dispatch_error) = self
+            .repository
+            .find_purchase_order(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Purchase order not found"))?;
+        let items = self.repository.purchase_order_items(id).await?;
+        Ok(PurchaseOrderTracking {
+            detail: PurchaseOrderDetail::new(purchase_order, items),
+            dispatch_attempts,
+            dispatch_error,
+            events: self.repository.events(id).await?,
+            discrepancies: self.repository.discrepancies(id).await?,
+        })
+    }
+
+    /// The order behind an acknowledgment link
+    pub async fn find_by_token(&self, token: &str) -> Result<PurchaseOrderDetail> {
+        let purchase_order = self
+            .repository
+            .find_by_ack_token(token)
+            .await?
+            .ok_or_else(|| Error::not_found("Purchase order not found"))?;
+        let items = self.repository.purchase_order_items(purchase_order.id).await?;
+        Ok(PurchaseOrderDetail::new(purchase_order, items))
+    }
+
+    /// Acknowledge an order from the supplier's link
+    pub async fn acknowledge_by_token(&self, token: &str, request: AcknowledgePurchaseOrderRequest) -> Result<PurchaseOrderTracking> {
+        let detail = self.find_by_token(token).await?;
+        self.acknowledge(detail.purchase_order.id, None, request).await
+    }
+
+    /// Record the supplier's acknowledgment; confirmed lines that differ
+    /// from the order become discrepancies
+    pub async fn acknowledge(
+        &self,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+        request: AcknowledgePurchaseOrderRequest,
+    ) -> Result<PurchaseOrderTracking> {
+        if request.supplier_reference.as_ref().is_some_and(|reference| reference.len() > 100) {
+            return Err(Error::validation("supplier_reference is limited to 100 characters"));
+        }
+        let tracking = self.tracking(id).await?;
+        let purchase_order = &tracking.detail.purchase_order;
+        if !purchase_order.status.can_receive() {
+            return Err(Error::validation("Only placed purchase orders can be acknowledged"));
+        }
+
+        let found = acknowledgment_discrepancies(&tracking.detail.items, &request.items, self.config.price_tolerance_percent)?;
+        self.repository.acknowledge(id, actor_id, &request).await?;
+        if !found.is_empty() {
+            self.record_discrepancies(purchase_order, &found).await?;
+        }
+        self.tracking(id).await
+    }
+
+    /// Match a delivery against the order; returns the discrepancies found
+    pub async fn match_receipt(&self, id: Uuid, received: &[ReceivedItem]) -> Result<Vec<Discrepancy>> {
+        let tracking = self.tracking(id).await?;
+        let found = receipt_discrepancies(&tracking.detail.items, received, self.config.price_tolerance_percent);
+        if found.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.record_discrepancies(&tracking.detail.purchase_order, &found).await
+    }
+
+    /// Discrepancies, newest first
+    pub async fn list_discrepancies(&self, open_only: bool, supplier_id: Option<Uuid>, limit: i64) -> Result<Vec<Discrepancy>> {
+        self.repository
+            .list_discrepancies(open_only, supplier_id, limit.clamp(1, MAX_LIST_LIMIT))
+            .await
+    }
+
+    /// Resolve a discrepancy
+    pub async fn resolve_discrepancy(&self, id: Uuid, actor_id: Uuid, request: ResolveDiscrepancyRequest) -> Result<Discrepancy> {
+        if request.resolution.trim().is_empty() {
+            return Err(Error::validation("Describe the resolution"));
+        }
+        self.repository
+            .resolve_discrepancy(id, actor_id, request.resolution.trim())
+            .await?
+            .ok_or_else(|| Error::not_found("Open discrepancy not found"))
+    }
+
+    /// Send what is pending (or failed and retriable) and alert staff about
+    /// unacknowledged and late orders
+    pub async fn run(&self) -> Result<DispatchReport> {
+        let mut report = DispatchReport::default();
+        for id in self.repository.dispatchable(self.config.max_dispatch_attempts).await? {
+            match self.dispatch(id).await {
+                Ok(_) => report.sent += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to dispatch purchase order {}: {}", id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        if self.config.ack_overdue_hours > 0 {
+            let unacknowledged = self.repository.ack_overdue(self.config.ack_overdue_hours).await?;
+            for purchase_order in &unacknowledged {
+                let message = format!(
+                    "{} was sent {} hours ago and the supplier has not acknowledged it.",
+                    purchase_order.po_number, self.config.ack_overdue_hours
+                );
+                self.alert(purchase_order, "not acknowledged", &message).await;
+            }
+            let ids: Vec<Uuid> = unacknowledged.iter().map(|order| order.id).collect();
+            self.repository.mark_alerted(&ids, PurchaseOrderEventType::AckOverdue).await?;
+            report.ack_overdue = ids.len();
+        }
+
+        let late = self.repository.late().await?;
+        for purchase_order in &late {
+            let expected = purchase_order.expected_at.map(|at| at.date_naive().to_string()).unwrap_or_default();
+            let message = format!(
+                "{} was expected on {} and has not been fully received.",
+                purchase_order.po_number, expected
+            );
+            self.alert(purchase_order, "late", &message).await;
+        }
+        let ids: Vec<Uuid> = late.iter().map(|order| order.id).collect();
+        self.repository.mark_alerted(&ids, PurchaseOrderEventType::Overdue).await?;
+        report.overdue = ids.len();
+        Ok(report)
+    }
+
+    /// Send a placed order to its supplier now
+    pub async fn dispatch(&self, id: Uuid) -> Result<PurchaseOrderTracking> {
+        let tracking = self.tracking(id).await?;
+        let purchase_order = &tracking.detail.purchase_order;
+        if !purchase_order.status.can_receive() {
+            return Err(Error::validation("Only placed purchase orders can be sent to the supplier"));
+        }
+
+        let settings = self.settings(purchase_order.supplier_id).await?;
+        match self.send(&tracking.detail, &settings).await {
+            Ok(()) => {
+                self.repository.mark_dispatched(id, settings.method).await?;
+                self.tracking(id).await
+            }
+            Err(e) => {
+                self.repository.mark_dispatch_failed(id, &e.to_string()).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn send(&self, detail: &PurchaseOrderDetail, settings: &SupplierDispatchSettings) -> Result<()> {
+        match settings.method {
+            PoDispatchMethod::Manual => Ok(()),
+            PoDispatchMethod::Email => {
+                let supplier = self
+                    .repository
+                    .find_supplier(detail.purchase_order.supplier_id)
+                    .await?
+                    .ok_or_else(|| Error::not_found("Supplier not found"))?;
+                let Some(email) = settings.email.clone().or(supplier.email) else {
+                    return Err(Error::validation("The supplier has no email for purchase orders"));
+                };
+                let Some(ref notifications) = self.notifications else {
+                    return Err(Error::validation("Notifications are not configured"));
+                };
+                let token = self
+                    .repository
+                    .ack_token(detail.purchase_order.id)
+                    .await?
+                    .ok_or_else(|| Error::validation("Purchase order has no acknowledgment token"))?;
+                let ack_url = format!(
+                    "{}/api/v1/purchase-orders/acknowledge/{}",
+                    self.config.public_url.trim_end_matches('/'),
+                    token
+                );
+                let notification = NotificationFactory::purchase_order(detail, &supplier.name, &ack_url, Recipient::email(email, None));
+                notifications.create(&notification).await?;
+                Ok(())
+            }
+            PoDispatchMethod::Sftp => {
+                let target = settings
+                    .sftp_target
+                    .as_deref()
+                    .ok_or_else(|| Error::validation("SFTP dispatch needs an sftp_target"))?;
+                self.upload(target, &detail.purchase_order.po_number, &edi_csv(detail)?).await
+            }
+        }
+    }
+
+    /// Upload the CSV with the `sftp` client in batch mode
+    async fn upload(&self, target: &str, po_number: &str, csv: &str) -> Result<()> {
+        let (host, directory) = parse_sftp_target(target)?;
+        let local = std::env::temp_dir().join(format!("rcommerce-{}-{}.csv", Uuid::new_v4(), po_number));
+        tokio::fs::write(&local, csv)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to write purchase order CSV: {}", e)))?;
+
+        let remote = match directory {
+            "" => format!("{}.csv", po_number),
+            directory => format!("{}/{}.csv", directory, po_number),
+        };
+        let result = self.run_sftp(host, &format!("put \"{}\" \"{}\"\n", local.display(), remote)).await;
+        let _ = tokio::fs::remove_file(&local).await;
+        result
+    }
+
+    async fn run_sftp(&self, host: &str, commands: &str) -> Result<()> {
+        let mut command = tokio::process::Command::new(&self.config.sftp_command);
+        command.args(["-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(ref identity) = self.config.sftp_identity_file {
+            command.args(["-i", identity]);
+        }
+        let mut child = command
+            .arg(host)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Error::Other(format!("Failed to run {}: {}", self.config.sftp_command, e)))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin
+            .write_all(commands.as_bytes())
+            .await
+            .map_err(|e| Error::Other(format!("Failed to run {}: {}", self.config.sftp_command, e)))?;
+        drop(stdin);
+
+        let output = tokio::time::timeout(Duration::from_secs(self.config.sftp_timeout_secs), child.wait_with_output())
+            .await
+            .map_err(|_| Error::Other("SFTP upload timed out".to_string()))?
+            .map_err(|e| Error::Other(format!("Failed to run {}: {}", self.config.sftp_command, e)))?;
+        if !output.status.success() {
+            return Err(Error::Other(format!(
+                "SFTP upload failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn record_discrepancies(&self, purchase_order: &PurchaseOrder, found: &[NewDiscrepancy]) -> Result<Vec<Discrepancy>> {
+        let recorded = self.repository.add_discrepancies(purchase_order.id, found).await?;
+        let lines: Vec<String> = found
+            .iter()
+            .map(|discrepancy| {
+                format!(
+                    "- {:?} {:?} on line {}: expected {}, got {}",
+                    discrepancy.source, discrepancy.kind, discrepancy.item_id, discrepancy.expected, discrepancy.actual
+                )
+            })
+            .collect();
+        let message = format!(
+            "{} has {} discrepancies to review:\n\n{}",
+            purchase_order.po_number,
+            found.len(),
+            lines.join("\n")
+        );
+        self.alert(purchase_order, "discrepancies", &message).await;
+        Ok(recorded)
+    }
+
+    /// Alert the purchasing team; failures are only logged
+    async fn alert(&self, purchase_order: &PurchaseOrder, what: &str, message: &str) {
+        tracing::info!("Purchase order {} {}", purchase_order.po_number, what);
+        let Some(ref notifications) = self.notifications else {
+            return;
+        };
+        if self.config.alert_roles.is_empty() {
+            return;
+        }
+        let staff = match self.repository.find_staff(&self.config.alert_roles).await {
+            Ok(staff) => staff,
+            Err(e) => {
+                tracing::warn!("Failed to alert staff about purchase order {}: {}", purchase_order.po_number, e);
+                return;
+            }
+        };
+        for (_, email) in staff {
+            let notification = NotificationFactory::purchase_order_alert(purchase_order, what, message, Recipient::email(email, None));
+            if let Err(e) = notifications.create(&notification).await {
+                tracing::warn!("Failed to alert staff about purchase order {}: {}", purchase_order.po_number, e);
+            }
+        }
+    }
+}
+
+/// The `po_dispatch` recurring job
+pub struct PurchaseOrderDispatchJob<R: PoDispatchRepository> {
+    dispatch: Arc<PurchaseOrderDispatchService<R>>,
+}
+
+impl<R: PoDispatchRepository> PurchaseOrderDispatchJob<R> {
+    pub fn new(dispatch: Arc<PurchaseOrderDispatchService<R>>) -> Self {
+        Self { dispatch }
+    }
+}
+
+#[async_trait]
+impl<R: PoDispatchRepository + 'static> RecurringJob for PurchaseOrderDispatchJob<R> {
+    fn name(&self) -> &str {
+        "po_dispatch"
+    }
+
+    fn description(&self) -> &str {
+        "Send placed purchase orders to suppliers and alert staff about unacknowledged and late ones"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.dispatch.config().dispatch_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.dispatch.run().await?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::PurchaseOrderStatus;
+    use rust_decimal_macros::dec;
+
+    fn item(quantity_ordered: i32, unit_cost: Option<Decimal>) -> PurchaseOrderItem {
+        PurchaseOrderItem {
+            id: Uuid::new_v4(),
+            purchase_order_id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            supplier_sku: Some("BEAN-1KG".to_string()),
+            quantity_ordered,
+            quantity_received: 0,
+            unit_cost,
+            confirmed_quantity: None,
+            confirmed_unit_cost: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_acknowledgment_discrepancies() {
+        let items = vec![item(10, Some(dec!(4.00))), item(5, None)];
+        let acknowledged = vec![
+            AcknowledgedItem { item_id: items[0].id, quantity: Some(8), unit_cost: Some(dec!(4.02)) },
+            AcknowledgedItem { item_id: items[1].id, quantity: Some(5), unit_cost: Some(dec!(9.00)) },
+        ];
+
+        let found = acknowledgment_discrepancies(&items, &acknowledged, Decimal::ZERO).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].kind, found[0].expected, found[0].actual), (DiscrepancyKind::Quantity, dec!(10), dec!(8)));
+        assert_eq!((found[1].kind, found[1].actual), (DiscrepancyKind::Price, dec!(4.02)));
+
+        // 0.5% of 4.00 covers 0.02
+        let found = acknowledgment_discrepancies(&items, &acknowledged, dec!(0.5)).unwrap();
+        assert_eq!(found.len(), 1);
+
+        let unknown = vec![AcknowledgedItem { item_id: Uuid::new_v4(), quantity: None, unit_cost: None }];
+        assert!(acknowledgment_discrepancies(&items, &unknown, Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_receipt_discrepancies() {
+        let mut line = item(10, Some(dec!(4.00)));
+        line.confirmed_unit_cost = Some(dec!(3.80));
+        let received = vec![ReceivedItem {
+            item_id: line.id,
+            quantity: 6,
+            invoiced_quantity: Some(8),
+            invoiced_unit_cost: Some(dec!(4.00)),
+        }];
+
+        let found = receipt_discrepancies(&[line.clone()], &received, Decimal::ZERO);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].kind, found[0].expected, found[0].actual), (DiscrepancyKind::Quantity, dec!(8), dec!(6)));
+        // Matched against the confirmed cost
+        assert_eq!((found[1].kind, found[1].expected), (DiscrepancyKind::Price, dec!(3.80)));
+
+        let matching = vec![ReceivedItem { item_id: line.id, quantity: 6, ..Default::default() }];
+        assert!(receipt_discrepancies(&[line], &matching, Decimal::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_edi_csv_and_sftp_target() {
+        let order_id = Uuid::new_v4();
+        let detail = PurchaseOrderDetail::new(
+            PurchaseOrder {
+                id: order_id,
+                po_number: "PO-1001".to_string(),
+                supplier_id: Uuid::new_v4(),
+                location_id: Uuid::new_v4(),
+                status: PurchaseOrderStatus::Ordered,
+                currency: "EUR".to_string(),
+                expected_at: "2026-11-02T00:00:00Z".parse().ok(),
+                notes: None,
+                auto_drafted: false,
+                created_by: None,
+                ordered_at: None,
+                received_at: None,
+                cancelled_at: None,
+                dispatch_status: Some(PoDispatchStatus::Pending),
+                dispatched_at: None,
+                acknowledged_at: None,
+                supplier_reference: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            vec![item(12, Some(dec!(4.50)))],
+        );
+        let csv = edi_csv(&detail).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "po_number,line,supplier_sku,product_id,variant_id,quantity,unit_cost,currency,expected_date");
+        assert!(lines[1].starts_with("PO-1001,1,BEAN-1KG,"));
+        assert!(lines[1].ends_with(",,12,4.50,EUR,2026-11-02"));
+
+        assert_eq!(parse_sftp_target("orders@edi.example.com:/inbound/").unwrap(), ("orders@edi.example.com", "/inbound"));
+        assert_eq!(parse_sftp_target("edi.example.com").unwrap(), ("edi.example.com", ""));
+        assert!(parse_sftp_target("-oProxyCommand=x:/in").is_err());
+        assert!(SaveDispatchSettingsRequest { method: PoDispatchMethod::Sftp, email: None, sftp_target: None }
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,839 @@
+//! Suppliers and purchase orders
+//!
+//! A purchase order brings stock from a supplier into one location. It is
+//! drafted (by staff, or by the `reorder` job for low stock), placed with
+//! the supplier, which adds its quantities to the location's
+//! `incoming_quantity`, and received in one or more deliveries, each of
+//! which moves the delivered units from `incoming_quantity` to
+//! `available_quantity` as an `in` stock movement. Cancelling a placed
+//! order takes what is still outstanding off `incoming_quantity`.
+//!
+//! The `reorder` job (`[purchasing]`) alerts staff once about each level
+//! at or below its `reorder_point`, and with `auto_draft` adds its
+//! `reorder_quantity` to a draft for the product's preferred supplier.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::notification::{LocationAlert, LowStockAlert};
+use super::po_dispatch::PoDispatchStatus;
+use super::StockAlertLevel;
+use crate::config::PurchasingConfig;
+use crate::events::{DomainEvent, EventBus, LowStock};
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, PurchaseOrderRepository};
+use crate::{Error, Result};
+
+/// Purchase orders listed at most
+pub const MAX_LIST_LIMIT: i64 = 200;
+
+/// Lines per purchase order at most
+const MAX_ITEMS: usize = 500;
+
+/// A supplier
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Supplier {
+    pub id: Uuid,
+    pub name: String,
+    pub code: Option<String>,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub currency: String,
+    /// Days from ordering to delivery
+    pub lead_time_days: i32,
+    pub notes: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create a supplier
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateSupplierRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1, max = 50))]
+    pub code: Option<String>,
+    pub contact_name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    #[validate(range(min = 0, max = 365))]
+    pub lead_time_days: Option<i32>,
+    pub notes: Option<String>,
+}
+
+/// Change a supplier; omitted fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateSupplierRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub code: Option<String>,
+    pub contact_name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    #[validate(range(min = 0, max = 365))]
+    pub lead_time_days: Option<i32>,
+    pub notes: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// A product (or variant) a supplier sells
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SupplierProduct {
+    pub id: Uuid,
+    pub supplier_id: Uuid,
+    pub product_id: Uuid,
+    /// None covers every variant of the product
+    pub variant_id: Option<Uuid>,
+    pub supplier_sku: Option<String>,
+    pub unit_cost: Option<Decimal>,
+    /// Drafted purchase orders go to the preferred supplier
+    pub is_preferred: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Add or change a product a supplier sells
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SupplierProductRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    #[validate(length(max = 100))]
+    pub supplier_sku: Option<String>,
+    pub unit_cost: Option<Decimal>,
+    /// Make this the supplier drafted purchase orders go to
+    #[serde(default)]
+    pub is_preferred: bool,
+}
+
+/// Purchase order status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "purchase_order_status", rename_all = "snake_case")]
+pub enum PurchaseOrderStatus {
+    /// Not sent to the supplier yet; lines can change
+    Draft,
+    /// Placed with the supplier; its quantities are incoming
+    Ordered,
+    PartiallyReceived,
+    Received,
+    Cancelled,
+}
+
+impl PurchaseOrderStatus {
+    /// Whether deliveries can be received against the order
+    pub fn can_receive(&self) -> bool {
+        matches!(self, PurchaseOrderStatus::Ordered | PurchaseOrderStatus::PartiallyReceived)
+    }
+
+    /// Whether the order can still be cancelled
+    pub fn can_cancel(&self) -> bool {
+        !matches!(self, PurchaseOrderStatus::Received | PurchaseOrderStatus::Cancelled)
+    }
+}
+
+/// A purchase order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PurchaseOrder {
+    pub id: Uuid,
+    pub po_number: String,
+    pub supplier_id: Uuid,
+    /// Where the stock is delivered
+    pub location_id: Uuid,
+    pub status: PurchaseOrderStatus,
+    pub currency: String,
+    pub expected_at: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+    /// Drafted by the `reorder` job
+    pub auto_drafted: bool,
+    pub created_by: Option<Uuid>,
+    pub ordered_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    /// Sending the order to the supplier; None until it is placed
+    pub dispatch_status: Option<PoDispatchStatus>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// The supplier's order number, from its acknowledgment
+    pub supplier_reference: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A line of a purchase order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PurchaseOrderItem {
+    pub id: Uuid,
+    pub purchase_order_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_sku: Option<String>,
+    pub quantity_ordered: i32,
+    pub quantity_received: i32,
+    pub unit_cost: Option<Decimal>,
+    /// What the supplier acknowledged
+    pub confirmed_quantity: Option<i32>,
+    pub confirmed_unit_cost: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PurchaseOrderItem {
+    /// Units still to be delivered
+    pub fn outstanding(&self) -> i32 {
+        self.quantity_ordered - self.quantity_received
+    }
+}
+
+/// A purchase order with its lines
+#[derive(Debug, Clone, Serialize)]
+pub struct PurchaseOrderDetail {
+    #[serde(flatten)]
+    pub purchase_order: PurchaseOrder,
+    pub items: Vec<PurchaseOrderItem>,
+    /// Cost of the ordered quantities (lines without a cost count as zero)
+    pub total: Decimal,
+}
+
+impl PurchaseOrderDetail {
+    pub fn new(purchase_order: PurchaseOrder, items: Vec<PurchaseOrderItem>) -> Self {
+        let total = items
+            .iter()
+            .map(|item| item.unit_cost.unwrap_or_default() * Decimal::from(item.quantity_ordered))
+            .sum();
+        Self { purchase_order, items, total }
+    }
+}
+
+/// A line to order; the cost and supplier SKU default to the supplier's
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PurchaseOrderItemInput {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    #[validate(range(min = 1, max = 1_000_000))]
+    pub quantity: i32,
+    pub unit_cost: Option<Decimal>,
+    #[validate(length(max = 100))]
+    pub supplier_sku: Option<String>,
+}
+
+/// Draft a purchase order
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePurchaseOrderRequest {
+    pub supplier_id: Uuid,
+    pub location_id: Uuid,
+    /// Defaults to now plus the supplier's lead time
+    pub expected_at: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+    pub items: Vec<PurchaseOrderItemInput>,
+}
+
+/// Change a purchase order; lines can only change while it is a draft
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePurchaseOrderRequest {
+    pub expected_at: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+    /// Replaces every line
+    pub items: Option<Vec<PurchaseOrderItemInput>>,
+}
+
+/// Units delivered for one line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReceivedItem {
+    pub item_id: Uuid,
+    pub quantity: i32,
+    /// Units on the supplier's invoice or packing slip, matched against
+    /// the units received
+    #[serde(default)]
+    pub invoiced_quantity: Option<i32>,
+    /// Invoiced cost per unit, matched against the order's
+    #[serde(default)]
+    pub invoiced_unit_cost: Option<Decimal>,
+}
+
+/// Receive a delivery against a purchase order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceivePurchaseOrderRequest {
+    pub items: Vec<ReceivedItem>,
+    /// Recorded on the stock movements
+    pub notes: Option<String>,
+}
+
+/// An inventory level at or below its reorder point
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LowStockLevel {
+    pub level_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub product_name: String,
+    pub location_id: Uuid,
+    pub location_name: String,
+    pub available_quantity: i32,
+    pub incoming_quantity: i32,
+    pub reorder_point: i32,
+    pub reorder_quantity: i32,
+    /// Staff were already alerted
+    pub alerted: bool,
+    /// A draft purchase order for the location already has the product
+    pub drafted: bool,
+    /// The preferred (or only) active supplier of the product
+    pub supplier_id: Option<Uuid>,
+    pub supplier_sku: Option<String>,
+    pub unit_cost: Option<Decimal>,
+    pub supplier_currency: Option<String>,
+    pub lead_time_days: Option<i32>,
+}
+
+impl LowStockLevel {
+    /// Whether the level should be added to a draft purchase order
+    pub fn needs_reorder(&self) -> bool {
+        self.reorder_quantity > 0
+            && !self.drafted
+            && self.available_quantity + self.incoming_quantity <= self.reorder_point
+    }
+
+    /// Critical at or below half the reorder point
+    pub fn alert_level(&self) -> StockAlertLevel {
+        if self.available_quantity <= self.reorder_point / 2 {
+            StockAlertLevel::Critical
+        } else {
+            StockAlertLevel::Low
+        }
+    }
+
+    fn to_alert(&self) -> LowStockAlert {
+        LowStockAlert {
+            product_id: self.product_id,
+            product_name: self.product_name.clone(),
+            current_stock: self.available_quantity,
+            threshold: self.reorder_point,
+            alert_level: self.alert_level(),
+            recommended_reorder_quantity: self.reorder_quantity,
+            locations_affected: vec![LocationAlert {
+                location_id: self.location_id,
+                location_name: self.location_name.clone(),
+                current_stock: self.available_quantity,
+                alert_level: self.alert_level(),
+            }],
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Outcome of a reorder check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReorderReport {
+    /// Levels at or below their reorder point
+    pub low_stock: usize,
+    /// Levels staff were alerted about this time
+    pub alerted: usize,
+    /// Lines added to draft purchase orders
+    pub drafted_items: usize,
+    /// Draft purchase orders created or added to
+    pub purchase_orders: Vec<Uuid>,
+    /// Levels to reorder that have no active supplier
+    pub without_supplier: usize,
+}
+
+impl std::fmt::Display for ReorderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} levels low on stock, {} alerted", self.low_stock, self.alerted)?;
+        if self.drafted_items > 0 {
+            write!(
+                f,
+                ", drafted {} lines on {} purchase orders",
+                self.drafted_items,
+                self.purchase_orders.len()
+            )?;
+        }
+        if self.without_supplier > 0 {
+            write!(f, ", {} without a supplier", self.without_supplier)?;
+        }
+        Ok(())
+    }
+}
+
+/// Supplier and purchase order service
+pub struct PurchasingService<R: PurchaseOrderRepository> {
+    repository: R,
+    config: PurchasingConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+    events: Option<EventBus>,
+}
+
+impl<R: PurchaseOrderRepository> PurchasingService<R> {
+    pub fn new(repository: R, config: PurchasingConfig) -> Self {
+        Self {
+            repository,
+            config,
+            notifications: None,
+            events: None,
+        }
+    }
+
+    /// Publish `inventory.low_stock` events for new low stock
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Queue low stock notifications (without it, low stock is only reported)
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &PurchasingConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create a supplier
+    pub async fn create_supplier(&self, request: CreateSupplierRequest) -> Result<Supplier> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.repository.create_supplier(&request).await
+    }
+
+    /// Update a supplier
+    pub async fn update_supplier(&self, id: Uuid, request: UpdateSupplierRequest) -> Result<Supplier> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.repository
+            .update_supplier(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))
+    }
+
+    /// Get a supplier
+    pub async fn get_supplier(&self, id: Uuid) -> Result<Supplier> {
+        self.repository
+            .find_supplier(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))
+    }
+
+    /// Add a product to a supplier, or change its cost, SKU or preference
+    pub async fn set_supplier_product(&self, supplier_id: Uuid, request: SupplierProductRequest) -> Result<SupplierProduct> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.unit_cost.is_some_and(|cost| cost.is_sign_negative()) {
+            return Err(Error::validation("unit_cost must not be negative"));
+        }
+        self.get_supplier(supplier_id).await?;
+        self.repository.set_supplier_product(supplier_id, &request).await
+    }
+
+    /// Draft a purchase order
+    pub async fn create_purchase_order(&self, actor_id: Option<Uuid>, request: CreatePurchaseOrderRequest) -> Result<PurchaseOrderDetail> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let supplier = self.get_supplier(request.supplier_id).await?;
+        if !supplier.is_active {
+            return Err(Error::validation("Supplier is not active"));
+        }
+
+        let request = CreatePurchaseOrderRequest {
+            expected_at: Some(
+                request
+                    .expected_at
+                    .unwrap_or_else(|| Utc::now() + Duration::days(i64::from(supplier.lead_time_days))),
+            ),
+            items: self.resolve_items(supplier.id, request.items).await?,
+            ..request
+        };
+        let purchase_order = self
+            .repository
+            .create_purchase_order(&request, &supplier.currency, actor_id)
+            .await?;
+        self.get_purchase_order(purchase_order.id).await
+    }
+
+    /// Update a purchase order's dates and notes, or a draft's lines
+    pub async fn update_purchase_order(&self, id: Uuid, request: UpdatePurchaseOrderRequest) -> Result<PurchaseOrderDetail> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let purchase_order = self.find(id).await?;
+        if !purchase_order.status.can_cancel() {
+            return Err(Error::validation("Received and cancelled purchase orders can't be changed"));
+        }
+
+        let items = match request.items {
+            Some(_) if purchase_order.status != PurchaseOrderStatus::Draft => {
+                return Err(Error::validation("Lines can only be changed while the purchase order is a draft"));
+            }
+            Some(items) => Some(self.resolve_items(purchase_order.supplier_id, items).await?),
+            None => None,
+        };
+        self.repository
+            .update_purchase_order(id, request.expected_at, request.notes.as_deref(), items.as_deref())
+            .await?
+            .ok_or_else(|| Error::validation("Purchase order changed status; try again"))?;
+        self.get_purchase_order(id).await
+    }
+
+    /// Get a purchase order with its lines
+    pub async fn get_purchase_order(&self, id: Uuid) -> Result<PurchaseOrderDetail> {
+        let purchase_order = self.find(id).await?;
+        let items = self.repository.purchase_order_items(id).await?;
+        Ok(PurchaseOrderDetail::new(purchase_order, items))
+    }
+
+    /// Place a draft with the supplier; its quantities become incoming
+    pub async fn place(&self, id: Uuid) -> Result<PurchaseOrderDetail> {
+        let purchase_order = self.find(id).await?;
+        if purchase_order.status != PurchaseOrderStatus::Draft {
+            return Err(Error::validation("Only draft purchase orders can be placed"));
+        }
+        self.repository.place(id).await?;
+        self.get_purchase_order(id).await
+    }
+
+    /// Receive a delivery; delivered units move from incoming to available
+    pub async fn receive(&self, id: Uuid, request: ReceivePurchaseOrderRequest) -> Result<PurchaseOrderDetail> {
+        validate_received(&request.items)?;
+        let purchase_order = self.find(id).await?;
+        if !purchase_order.status.can_receive() {
+            return Err(Error::validation("Only placed purchase orders can be received"));
+        }
+        self.repository.receive(id, &request.items, request.notes.as_deref()).await?;
+        self.get_purchase_order(id).await
+    }
+
+    /// Cancel a purchase order; what is still outstanding is no longer incoming
+    pub async fn cancel(&self, id: Uuid) -> Result<PurchaseOrderDetail> {
+        let purchase_order = self.find(id).await?;
+        if !purchase_order.status.can_cancel() {
+            return Err(Error::validation("Received and cancelled purchase orders can't be cancelled"));
+        }
+        self.repository.cancel(id).await?;
+        self.get_purchase_order(id).await
+    }
+
+    /// Alert staff about new low stock and, with `auto_draft`, draft
+    /// purchase orders for it
+    pub async fn check_reorders(&self) -> Result<ReorderReport> {
+        self.repository.clear_low_stock_alerts().await?;
+        let levels = self.repository.low_stock_levels().await?;
+        let mut report = ReorderReport {
+            low_stock: levels.len(),
+            ..Default::default()
+        };
+
+        let new: Vec<&LowStockLevel> = levels.iter().filter(|level| !level.alerted).collect();
+        if !new.is_empty() {
+            self.publish_low_stock(&new);
+            if let Err(e) = self.notify_low_stock(&new).await {
+                tracing::warn!("Failed to notify staff of low stock: {}", e);
+            }
+            let ids: Vec<Uuid> = new.iter().map(|level| level.level_id).collect();
+            self.repository.mark_low_stock_alerted(&ids).await?;
+            report.alerted = ids.len();
+        }
+
+        if !self.config.auto_draft {
+            return Ok(report);
+        }
+
+        // One draft per supplier and location
+        let mut drafts: BTreeMap<(Uuid, Uuid), Vec<&LowStockLevel>> = BTreeMap::new();
+        for level in levels.iter().filter(|level| level.needs_reorder()) {
+            match level.supplier_id {
+                Some(supplier_id) => drafts.entry((supplier_id, level.location_id)).or_default().push(level),
+                None => report.without_supplier += 1,
+            }
+        }
+
+        for ((supplier_id, location_id), levels) in drafts {
+            let items: Vec<PurchaseOrderItemInput> = levels
+                .iter()
+                .map(|level| PurchaseOrderItemInput {
+                    product_id: level.product_id,
+                    variant_id: level.variant_id,
+                    quantity: level.reorder_quantity,
+                    unit_cost: level.unit_cost,
+                    supplier_sku: level.supplier_sku.clone(),
+                })
+                .collect();
+            let lead_time_days = levels[0].lead_time_days.unwrap_or_default();
+            let currency = levels[0].supplier_currency.clone().unwrap_or_else(|| "USD".to_string());
+            let expected_at = Utc::now() + Duration::days(i64::from(lead_time_days));
+
+            let purchase_order_id = self
+                .repository
+                .add_to_draft(supplier_id, location_id, &currency, expected_at, &items)
+                .await?;
+            tracing::info!(
+                "Drafted {} low stock lines on purchase order {}",
+                items.len(),
+                purchase_order_id
+            );
+            report.drafted_items += items.len();
+            report.purchase_orders.push(purchase_order_id);
+        }
+        Ok(report)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<PurchaseOrder> {
+        self.repository
+            .find_purchase_order(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Purchase order not found"))
+    }
+
+    /// Check lines and fill in the supplier's costs and SKUs
+    async fn resolve_items(&self, supplier_id: Uuid, items: Vec<PurchaseOrderItemInput>) -> Result<Vec<PurchaseOrderItemInput>> {
+        validate_items(&items)?;
+        let mut resolved = Vec::with_capacity(items.len());
+        for mut item in items {
+            if item.unit_cost.is_none() || item.supplier_sku.is_none() {
+                if let Some(known) = self
+                    .repository
+                    .find_supplier_product(supplier_id, item.product_id, item.variant_id)
+                    .await?
+                {
+                    item.unit_cost = item.unit_cost.or(known.unit_cost);
+                    item.supplier_sku = item.supplier_sku.or(known.supplier_sku);
+                }
+            }
+            resolved.push(item);
+        }
+        Ok(resolved)
+    }
+
+    fn publish_low_stock(&self, levels: &[&LowStockLevel]) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        for level in levels {
+            events.publish(DomainEvent::InventoryLowStock(LowStock {
+                product_id: level.product_id,
+                variant_id: level.variant_id,
+                location_id: level.location_id,
+                available_quantity: level.available_quantity,
+                reorder_point: level.reorder_point,
+            }));
+        }
+    }
+
+    async fn notify_low_stock(&self, levels: &[&LowStockLevel]) -> Result<()> {
+        let Some(ref notifications) = self.notifications else {
+            return Ok(());
+        };
+        if !self.config.notify_low_stock || self.config.alert_roles.is_empty() {
+            return Ok(());
+        }
+
+        let staff = self.repository.find_staff(&self.config.alert_roles).await?;
+        for level in levels {
+            let alert = level.to_alert();
+            for (_, email) in &staff {
+                let notification = NotificationFactory::low_stock_alert(&alert, Recipient::email(email.clone(), None));
+                notifications.create(&notification).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lines need a positive quantity, a non-negative cost and one line per product
+pub fn validate_items(items: &[PurchaseOrderItemInput]) -> Result<()> {
+    if items.is_empty() || items.len() > MAX_ITEMS {
+        return Err(Error::validation(format!("Purchase orders need 1 to {} lines", MAX_ITEMS)));
+    }
+    let mut seen = HashSet::new();
+    for item in items {
+        item.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if item.unit_cost.is_some_and(|cost| cost.is_sign_negative()) {
+            return Err(Error::validation("unit_cost must not be negative"));
+        }
+        if !seen.insert((item.product_id, item.variant_id)) {
+            return Err(Error::validation(format!(
+                "Product {} is on more than one line",
+                item.product_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Deliveries need a positive quantity for each line, once
+pub fn validate_received(items: &[ReceivedItem]) -> Result<()> {
+    if items.is_empty() {
+        return Err(Error::validation("Receive at least one line"));
+    }
+    let mut seen = HashSet::new();
+    for item in items {
+        if item.quantity <= 0 {
+            return Err(Error::validation("Received quantities must be positive"));
+        }
+        if !seen.insert(item.item_id) {
+            return Err(Error::validation(format!("Line {} is received more than once", item.item_id)));
+        }
+    }
+    Ok(())
+}
+
+/// The `reorder` recurring job
+pub struct ReorderJob<R: PurchaseOrderRepository> {
+    purchasing: Arc<PurchasingService<R>>,
+}
+
+impl<R: PurchaseOrderRepository> ReorderJob<R> {
+    pub fn new(purchasing: Arc<PurchasingService<R>>) -> Self {
+        Self { purchasing }
+    }
+}
+
+#[async_trait]
+impl<R: PurchaseOrderRepository + 'static> RecurringJob for ReorderJob<R> {
+    fn name(&self) -> &str {
+        "reorder"
+    }
+
+    fn description(&self) -> &str {
+        "Alert staff about low stock and draft purchase orders for it"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.purchasing.config().reorder_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.purchasing.check_reorders().await?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn level(available: i32, incoming: i32) -> LowStockLevel {
+        LowStockLevel {
+            level_id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            product_name: "Espresso beans".to_string(),
+            location_id: Uuid::new_v4(),
+            location_name: "Main warehouse".to_string(),
+            available_quantity: available,
+            incoming_quantity: incoming,
+            reorder_point: 20,
+            reorder_quantity: 100,
+            alerted: false,
+            drafted: false,
+            supplier_id: Some(Uuid::new_v4()),
+            supplier_sku: None,
+            unit_cost: None,
+            supplier_currency: None,
+            lead_time_days: None,
+        }
+    }
+
+    fn item(product_id: Uuid, quantity: i32) -> PurchaseOrderItemInput {
+        PurchaseOrderItemInput {
+            product_id,
+            variant_id: None,
+            quantity,
+            unit_cost: Some(dec!(4.50)),
+            supplier_sku: None,
+        }
+    }
+
+    #[test]
+    fn test_needs_reorder() {
+        assert!(level(15, 0).needs_reorder());
+        // Enough already on order
+        assert!(!level(15, 10).needs_reorder());
+        assert!(level(5, 10).needs_reorder());
+        assert!(!LowStockLevel { drafted: true, ..level(5, 0) }.needs_reorder());
+        assert!(!LowStockLevel { reorder_quantity: 0, ..level(5, 0) }.needs_reorder());
+
+        assert_eq!(level(10, 0).alert_level(), StockAlertLevel::Critical);
+        assert_eq!(level(15, 0).alert_level(), StockAlertLevel::Low);
+    }
+
+    #[test]
+    fn test_validate_items() {
+        let product_id = Uuid::new_v4();
+        assert!(validate_items(&[item(product_id, 10)]).is_ok());
+        assert!(validate_items(&[]).is_err());
+        assert!(validate_items(&[item(product_id, 0)]).is_err());
+        assert!(validate_items(&[item(product_id, 10), item(product_id, 5)]).is_err());
+
+        let line = Uuid::new_v4();
+        let received = |quantity| ReceivedItem { item_id: line, quantity, ..Default::default() };
+        assert!(validate_received(&[received(3)]).is_ok());
+        assert!(validate_received(&[received(-3)]).is_err());
+        assert!(validate_received(&[received(1), received(2)]).is_err());
+    }
+
+    #[test]
+    fn test_detail_total_and_status() {
+        let order_id = Uuid::new_v4();
+        let line = |quantity_ordered, unit_cost| PurchaseOrderItem {
+            id: Uuid::new_v4(),
+            purchase_order_id: order_id,
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            supplier_sku: None,
+            quantity_ordered,
+            quantity_received: 2,
+            unit_cost,
+            confirmed_quantity: None,
+            confirmed_unit_cost: None,
+            created_at: Utc::now(),
+        };
+        let items = vec![line(10, Some(dec!(4.50))), line(3, None)];
+        assert_eq!(items[0].outstanding(), 8);
+
+        let total: Decimal = PurchaseOrderDetail::new(
+            PurchaseOrder {
+                id: order_id,
+                po_number: "PO-1001".to_string(),
+                supplier_id: Uuid::new_v4(),
+                location_id: Uuid::new_v4(),
+                status: PurchaseOrderStatus::Ordered,
+                currency: "USD".to_string(),
+                expected_at: None,
+                notes: None,
+                auto_drafted: false,
+                created_by: None,
+                ordered_at: None,
+                received_at: None,
+                cancelled_at: None,
+                dispatch_status: None,
+                dispatched_at: None,
+                acknowledged_at: None,
+                supplier_reference: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            items,
+        )
+        .total;
+        assert_eq!(total, dec!(45.00));
+
+        assert!(PurchaseOrderStatus::PartiallyReceived.can_receive());
+        assert!(!PurchaseOrderStatus::Draft.can_receive());
+        assert!(PurchaseOrderStatus::Draft.can_cancel());
+        assert!(!PurchaseOrderStatus::Received.can_cancel());
+    }
+}
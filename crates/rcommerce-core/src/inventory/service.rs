@@ -1,5 +1,6 @@
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use sqlx::PgConnection;
 
 use crate::{Result, Error};
 use crate::inventory::{InventoryConfig, InventoryLocation, ProductInventory, LocationInventory, StockReservation, InventoryLevel, StockMovement};
@@ -102,6 +103,105 @@ impl InventoryService {
         Ok(reservation)
     }
     
+    /// Reserve stock for an item of a new order on the order's transaction,
+    /// at the active location with the most free stock. The item's stock
+    /// levels stay locked until the transaction ends, so concurrent orders
+    /// can't both take the last units; reserve an order's items sorted by
+    /// product and variant so they lock in the same order. Returns None
+    /// when the product's stock isn't managed or reservations are disabled,
+    /// and for stock kept only in `inventory_quantity` (no stock levels),
+    /// which is decremented right away instead.
+    pub async fn reserve_for_order(
+        &self,
+        conn: &mut PgConnection,
+        order_id: Uuid,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        quantity: i32,
+    ) -> Result<Option<StockReservation>> {
+        if !self.config.enable_reservations {
+            return Ok(None);
+        }
+        let (managed, backorders) = sqlx::query_as::<_, (bool, bool)>(
+            "SELECT inventory_management, inventory_policy = 'continue' FROM products WHERE id = $1"
+        )
+        .bind(product_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| Error::not_found("Product not found"))?;
+        if !managed {
+            return Ok(None);
+        }
+
+        // Lock the levels first; holds counted afterwards include every
+        // reservation committed by orders that held the lock before us
+        let levels = sqlx::query_as::<_, (Uuid, i32)>(
+            r#"
+            SELECT l.location_id, l.available_quantity
+            FROM inventory_levels l
+            JOIN inventory_locations loc ON loc.id = l.location_id AND loc.is_active
+            WHERE l.product_id = $1 AND l.variant_id IS NOT DISTINCT FROM $2
+            ORDER BY l.location_id
+            FOR UPDATE OF l
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .fetch_all(&mut *conn)
+        .await?;
+        if levels.is_empty() && !has_stock_levels(conn, product_id, variant_id).await? {
+            take_unit_stock(conn, product_id, variant_id, quantity, backorders).await?;
+            return Ok(None);
+        }
+        let held = sqlx::query_as::<_, (Uuid, i64)>(
+            r#"
+            SELECT location_id, SUM(quantity)::BIGINT
+            FROM stock_reservations
+            WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2
+              AND status = 'active' AND expires_at > NOW()
+            GROUP BY location_id
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let free: Vec<(Uuid, i64)> = levels
+            .into_iter()
+            .map(|(location_id, available)| {
+                let held = held.iter().find(|(id, _)| *id == location_id).map(|(_, held)| *held).unwrap_or(0);
+                (location_id, available as i64 - held)
+            })
+            .collect();
+        let Some(location_id) = pick_location(&free, quantity, backorders) else {
+            let available = free.iter().map(|(_, free)| (*free).max(0)).max().unwrap_or(0);
+            return Err(Error::validation(format!(
+                "Insufficient stock. Available: {}, Requested: {}",
+                available, quantity
+            )));
+        };
+
+        let expires_at = chrono::Utc::now() + chrono::Duration::minutes(self.config.reservation_timeout_minutes as i64);
+        let reservation = StockReservation::new(product_id, variant_id, location_id, order_id, quantity, expires_at);
+        sqlx::query(
+            r#"
+            INSERT INTO stock_reservations (id, product_id, variant_id, location_id, order_id, quantity, expires_at, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, 'active')
+            "#
+        )
+        .bind(reservation.id)
+        .bind(product_id)
+        .bind(variant_id)
+        .bind(location_id)
+        .bind(order_id)
+        .bind(quantity)
+        .bind(expires_at)
+        .execute(&mut *conn)
+        .await?;
+        Ok(Some(reservation))
+    }
+    
     /// Release reserved stock
     pub async fn release_reservation(&self, reservation_id: uuid::Uuid) -> Result<bool> {
         let result = sqlx::query(
@@ -223,18 +323,16 @@ impl InventoryService {
         Ok(reservations)
     }
     
-    /// Clean up expired reservations
+    /// Mark reservations past their timeout expired; their stock is free
+    /// again from the moment they expire, this only records it
     pub async fn cleanup_expired_reservations(&self) -> Result<i64> {
-        let expired = self.get_expired_reservations().await?;
-        let mut released_count = 0;
-        
-        for reservation in expired {
-            if self.release_reservation(reservation.id).await? {
-                released_count += 1;
-            }
-        }
+        let result = sqlx::query(
+            "UPDATE stock_reservations SET status = 'expired', updated_at = NOW() WHERE status = 'active' AND expires_at < NOW()"
+        )
+        .execute(self.db.pool())
+        .await?;
         
-        Ok(released_count)
+        Ok(result.rows_affected() as i64)
     }
     
     /// Helper: Get available stock
@@ -290,6 +388,63 @@ impl crate::services::Service for InventoryService {
     }
 }
 
+/// Whether any location, active or not, tracks the item's stock
+async fn has_stock_levels(conn: &mut PgConnection, product_id: Uuid, variant_id: Option<Uuid>) -> Result<bool> {
+    Ok(sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM inventory_levels WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2)"
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .fetch_one(&mut *conn)
+    .await?)
+}
+
+/// Take stock kept only in the variant's or product's `inventory_quantity`;
+/// the row stays locked until the order commits, and taking more than is
+/// left fails unless backorders are allowed
+async fn take_unit_stock(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    variant_id: Option<Uuid>,
+    quantity: i32,
+    backorders: bool,
+) -> Result<()> {
+    let (id, table) = match variant_id {
+        Some(variant_id) => (variant_id, "product_variants"),
+        None => (product_id, "products"),
+    };
+    let taken = sqlx::query(&format!(
+        "UPDATE {} SET inventory_quantity = inventory_quantity - $2, updated_at = NOW() \
+         WHERE id = $1 AND ($3 OR inventory_quantity >= $2)",
+        table
+    ))
+    .bind(id)
+    .bind(quantity)
+    .bind(backorders)
+    .execute(&mut *conn)
+    .await?;
+    if taken.rows_affected() == 0 {
+        let available = sqlx::query_scalar::<_, i32>(&format!("SELECT inventory_quantity FROM {} WHERE id = $1", table))
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .unwrap_or(0);
+        return Err(Error::validation(format!(
+            "Insufficient stock. Available: {}, Requested: {}",
+            available.max(0),
+            quantity
+        )));
+    }
+    Ok(())
+}
+
+/// The location to reserve `quantity` at: the one with the most free stock,
+/// if it has enough or backorders are allowed
+fn pick_location(free: &[(Uuid, i64)], quantity: i32, backorders: bool) -> Option<Uuid> {
+    let (location_id, most) = free.iter().max_by_key(|(_, free)| *free)?;
+    (backorders || *most >= quantity as i64).then_some(*location_id)
+}
+
 /// Low stock alert
 #[derive(Debug, Clone)]
 pub struct LowStockAlert {
@@ -319,4 +474,16 @@ mod tests {
         assert!(config.enable_reservations);
         assert_eq!(config.reservation_timeout_minutes, 30);
     }
+    
+    #[test]
+    fn test_pick_location() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let free = [(a, 2), (b, 5)];
+        assert_eq!(pick_location(&free, 3, false), Some(b));
+        assert_eq!(pick_location(&free, 5, false), Some(b));
+        assert_eq!(pick_location(&free, 6, false), None);
+        assert_eq!(pick_location(&free, 6, true), Some(b));
+        assert_eq!(pick_location(&[(a, -1)], 1, false), None);
+        assert_eq!(pick_location(&[], 1, true), None);
+    }
 }
\ No newline at end of file
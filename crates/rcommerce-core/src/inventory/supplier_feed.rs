@@ -0,0 +1,1306 @@
+//! Supplier feeds and dropship orders
+//!
+//! A supplier feed is the supplier's stock and price file (CSV, XML or
+//! JSON). The `supplier_feeds` job pulls it from `source_url` every
+//! `pull_interval_minutes`, or the supplier pushes it to
+//! `POST /api/v1/supplier-feeds/{supplier_id}` signed with the feed's
+//! `webhook_secret` (`X-Feed-Signature: sha256=<hex HMAC-SHA256 of the body>`).
+//!
+//! Rows are matched to the supplier's products by supplier SKU. The
+//! supplier's stock and cost are recorded on the supplier product;
+//! dropshipped products also take the supplier's stock as their own
+//! inventory, `update_prices` sets selling prices from costs through the
+//! margin rules, and `auto_disable` disables products the supplier has run
+//! out of (enabling them again once restocked, if the feed disabled them).
+//!
+//! With `dropship`, the `dropship` job routes the paid order lines of the
+//! supplier's products to it: one dropship order per order and supplier,
+//! POSTed as JSON to `order_webhook_url` (signed like pushed feeds) or
+//! emailed to the supplier. Failed sends are retried up to
+//! `max_send_attempts` times.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::config::SupplierFeedsConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, SupplierFeedRepository};
+use crate::{Error, Result};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header of the signature of pushed feeds and sent dropship orders
+pub const SIGNATURE_HEADER: &str = "X-Feed-Signature";
+
+/// Dropship orders listed at most
+pub const MAX_LIST_LIMIT: i64 = 200;
+
+/// Largest `max_feed_bytes`, and the body limit of feed routes
+pub const MAX_FEED_BYTES: usize = 100 * 1024 * 1024;
+
+/// Row errors kept in a sync report
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Feed file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "supplier_feed_format", rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// With a header row
+    Csv,
+    /// One `record_element` per product, fields as child elements or attributes
+    Xml,
+    /// An array of objects, at `records_path` or the top level
+    Json,
+}
+
+/// Where a feed keeps the SKU, stock and cost of each product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedMapping {
+    #[serde(default = "default_sku_field")]
+    pub sku_field: String,
+    #[serde(default = "default_quantity_field")]
+    pub quantity_field: String,
+    /// Without it, costs are left as they are
+    #[serde(default = "default_cost_field")]
+    pub cost_field: Option<String>,
+    /// XML element of each product
+    #[serde(default = "default_record_element")]
+    pub record_element: String,
+    /// Dotted path to the JSON array of products, e.g. `data.items`
+    #[serde(default)]
+    pub records_path: Option<String>,
+}
+
+fn default_sku_field() -> String {
+    "sku".to_string()
+}
+
+fn default_quantity_field() -> String {
+    "quantity".to_string()
+}
+
+fn default_cost_field() -> Option<String> {
+    Some("cost".to_string())
+}
+
+fn default_record_element() -> String {
+    "item".to_string()
+}
+
+impl Default for FeedMapping {
+    fn default() -> Self {
+        Self {
+            sku_field: default_sku_field(),
+            quantity_field: default_quantity_field(),
+            cost_field: default_cost_field(),
+            record_element: default_record_element(),
+            records_path: None,
+        }
+    }
+}
+
+/// Selling price from a supplier cost, for costs in `[min_cost, max_cost)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginRule {
+    #[serde(default)]
+    pub min_cost: Option<Decimal>,
+    #[serde(default)]
+    pub max_cost: Option<Decimal>,
+    /// Added to the cost, e.g. 40 for cost × 1.4
+    pub markup_percent: Decimal,
+    /// Added after the markup
+    #[serde(default)]
+    pub fixed_amount: Decimal,
+    /// Cents the price is rounded up to, e.g. 0.99
+    #[serde(default)]
+    pub price_ending: Option<Decimal>,
+}
+
+impl MarginRule {
+    pub fn matches(&self, cost: Decimal) -> bool {
+        self.min_cost.map_or(true, |min| cost >= min) && self.max_cost.map_or(true, |max| cost < max)
+    }
+
+    /// The selling price for a cost
+    pub fn price(&self, cost: Decimal) -> Decimal {
+        let price = (cost * (Decimal::ONE + self.markup_percent / Decimal::from(100)) + self.fixed_amount).round_dp(2);
+        match self.price_ending {
+            Some(ending) => {
+                let ended = price.trunc() + ending;
+                if ended < price {
+                    ended + Decimal::ONE
+                } else {
+                    ended
+                }
+            }
+            None => price,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.markup_percent.is_sign_negative() || self.fixed_amount.is_sign_negative() {
+            return Err(Error::validation("Margin rules need a non-negative markup_percent and fixed_amount"));
+        }
+        if let (Some(min), Some(max)) = (self.min_cost, self.max_cost) {
+            if min >= max {
+                return Err(Error::validation("Margin rule min_cost must be below max_cost"));
+            }
+        }
+        if self
+            .price_ending
+            .is_some_and(|ending| ending.is_sign_negative() || ending >= Decimal::ONE)
+        {
+            return Err(Error::validation("Margin rule price_ending must be at least 0 and below 1"));
+        }
+        Ok(())
+    }
+}
+
+/// The price of the first rule matching the cost
+pub fn apply_margin(rules: &[MarginRule], cost: Decimal) -> Option<Decimal> {
+    rules.iter().find(|rule| rule.matches(cost)).map(|rule| rule.price(cost))
+}
+
+/// A supplier's feed
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SupplierFeed {
+    pub id: Uuid,
+    pub supplier_id: Uuid,
+    pub format: FeedFormat,
+    /// Pulled by the `supplier_feeds` job; None for feeds that are only pushed
+    pub source_url: Option<String>,
+    pub pull_interval_minutes: i32,
+    /// Key of the signature of pushed feeds and sent dropship orders
+    pub webhook_secret: String,
+    pub mapping: Json<FeedMapping>,
+    pub margin_rules: Json<Vec<MarginRule>>,
+    pub update_prices: bool,
+    pub auto_disable: bool,
+    pub dropship: bool,
+    /// Dropship orders are POSTed here; without it they are emailed
+    pub order_webhook_url: Option<String>,
+    pub is_active: bool,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_report: Option<Json<FeedSyncReport>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create or replace a supplier's feed; the webhook secret is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSupplierFeedRequest {
+    pub format: FeedFormat,
+    pub source_url: Option<String>,
+    #[serde(default = "default_pull_interval_minutes")]
+    pub pull_interval_minutes: i32,
+    #[serde(default)]
+    pub mapping: FeedMapping,
+    #[serde(default)]
+    pub margin_rules: Vec<MarginRule>,
+    #[serde(default)]
+    pub update_prices: bool,
+    #[serde(default = "default_true")]
+    pub auto_disable: bool,
+    #[serde(default)]
+    pub dropship: bool,
+    pub order_webhook_url: Option<String>,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    /// Replace the webhook secret
+    #[serde(default)]
+    pub rotate_secret: bool,
+}
+
+fn default_pull_interval_minutes() -> i32 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SaveSupplierFeedRequest {
+    pub fn validate(&self) -> Result<()> {
+        for url in [&self.source_url, &self.order_webhook_url].into_iter().flatten() {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(Error::validation(format!("{} is not an http(s) URL", url)));
+            }
+        }
+        if !(5..=10_080).contains(&self.pull_interval_minutes) {
+            return Err(Error::validation("pull_interval_minutes must be between 5 and 10080"));
+        }
+        if self.mapping.sku_field.trim().is_empty() || self.mapping.quantity_field.trim().is_empty() {
+            return Err(Error::validation("The feed mapping needs a sku_field and quantity_field"));
+        }
+        if self.format == FeedFormat::Xml && self.mapping.record_element.trim().is_empty() {
+            return Err(Error::validation("XML feeds need a record_element"));
+        }
+        for rule in &self.margin_rules {
+            rule.validate()?;
+        }
+        if self.update_prices && (self.margin_rules.is_empty() || self.mapping.cost_field.is_none()) {
+            return Err(Error::validation("update_prices needs margin rules and a cost_field"));
+        }
+        Ok(())
+    }
+}
+
+/// A product's stock and cost in a feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedRow {
+    pub sku: String,
+    /// Negative stock counts as none
+    pub quantity: i32,
+    pub cost: Option<Decimal>,
+}
+
+/// A supplier product with what the feed may change
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FeedProduct {
+    pub supplier_product_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_sku: String,
+    pub unit_cost: Option<Decimal>,
+    pub disabled_by_feed: bool,
+    /// Of the variant, for variant supplier products
+    pub is_active: bool,
+    pub price: Decimal,
+}
+
+/// What a feed changes about one supplier product
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedUpdate {
+    pub supplier_product_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub supplier_stock: i32,
+    pub unit_cost: Option<Decimal>,
+    /// The product's inventory, for dropshipped products
+    pub inventory_quantity: Option<i32>,
+    pub price: Option<Decimal>,
+    pub is_active: Option<bool>,
+    pub disabled_by_feed: bool,
+}
+
+/// Outcome of a feed sync
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedSyncReport {
+    pub rows: usize,
+    /// Rows matching a supplier product
+    pub matched: usize,
+    /// Rows whose SKU the supplier has no product for
+    pub unmatched: usize,
+    /// Rows that could not be read
+    pub skipped: usize,
+    pub prices_updated: usize,
+    pub disabled: usize,
+    pub enabled: usize,
+    /// The first row errors
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+impl FeedSyncReport {
+    fn skip(&mut self, error: String) {
+        self.skipped += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+impl std::fmt::Display for FeedSyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} rows, {} matched", self.rows, self.matched)?;
+        if self.unmatched > 0 {
+            write!(f, ", {} unmatched", self.unmatched)?;
+        }
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        if self.prices_updated > 0 {
+            write!(f, ", {} prices updated", self.prices_updated)?;
+        }
+        if self.disabled > 0 || self.enabled > 0 {
+            write!(f, ", {} disabled, {} enabled", self.disabled, self.enabled)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a run of the `supplier_feeds` job
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedRunReport {
+    pub synced: usize,
+    pub failed: usize,
+}
+
+impl std::fmt::Display for FeedRunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} feeds synced, {} failed", self.synced, self.failed)
+    }
+}
+
+/// Dropship order status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "dropship_order_status", rename_all = "snake_case")]
+pub enum DropshipStatus {
+    Pending,
+    Sent,
+    /// Retried until `max_send_attempts`
+    Failed,
+}
+
+/// Paid order lines routed to a supplier
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DropshipOrder {
+    pub id: Uuid,
+    pub supplier_id: Uuid,
+    pub order_id: Uuid,
+    pub status: DropshipStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// The supplier's order number, when its webhook returns one
+    pub supplier_reference: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A line of a dropship order
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DropshipOrderItem {
+    pub id: Uuid,
+    pub dropship_order_id: Uuid,
+    pub order_item_id: Uuid,
+    pub supplier_sku: Option<String>,
+    pub title: String,
+    pub quantity: i32,
+    pub unit_cost: Option<Decimal>,
+}
+
+/// A dropship order with what the supplier needs to ship it
+#[derive(Debug, Clone, Serialize)]
+pub struct DropshipOrderDetail {
+    #[serde(flatten)]
+    pub dropship_order: DropshipOrder,
+    pub order_number: String,
+    pub email: String,
+    pub shipping_address: Option<serde_json::Value>,
+    pub items: Vec<DropshipOrderItem>,
+}
+
+/// What is POSTed to a supplier's order webhook
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DropshipOrderPayload {
+    /// The dropship order's ID
+    pub id: Uuid,
+    pub order_number: String,
+    /// The customer's email
+    pub email: String,
+    /// The order's shipping address as stored on the order
+    pub shipping_address: Option<serde_json::Value>,
+    pub items: Vec<DropshipOrderPayloadItem>,
+}
+
+/// A line of a dropship order webhook
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DropshipOrderPayloadItem {
+    /// The supplier's SKU
+    pub sku: Option<String>,
+    pub title: String,
+    pub quantity: i32,
+    pub unit_cost: Option<Decimal>,
+}
+
+impl DropshipOrderDetail {
+    /// What is POSTed to the supplier's order webhook
+    pub fn payload(&self) -> serde_json::Value {
+        let payload = DropshipOrderPayload {
+            id: self.dropship_order.id,
+            order_number: self.order_number.clone(),
+            email: self.email.clone(),
+            shipping_address: self.shipping_address.clone(),
+            items: self
+                .items
+                .iter()
+                .map(|item| DropshipOrderPayloadItem {
+                    sku: item.supplier_sku.clone(),
+                    title: item.title.clone(),
+                    quantity: item.quantity,
+                    unit_cost: item.unit_cost,
+                })
+                .collect(),
+        };
+        serde_json::to_value(payload).unwrap_or_default()
+    }
+}
+
+/// An unrouted paid order line of a dropshipped product
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DropshipLine {
+    pub order_item_id: Uuid,
+    pub order_id: Uuid,
+    pub supplier_id: Uuid,
+    pub supplier_sku: Option<String>,
+    pub title: String,
+    pub quantity: i32,
+    pub unit_cost: Option<Decimal>,
+}
+
+/// Outcome of a run of the `dropship` job
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DropshipReport {
+    /// Dropship orders created
+    pub routed: usize,
+    pub sent: usize,
+    pub failed: usize,
+}
+
+impl std::fmt::Display for DropshipReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} dropship orders routed, {} sent, {} failed", self.routed, self.sent, self.failed)
+    }
+}
+
+/// Hex HMAC-SHA256 of a body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether a `sha256=<hex>` (or bare hex) signature is the body's
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Read a feed's rows; rows that can't be read are counted in the report
+pub fn parse_feed(format: FeedFormat, mapping: &FeedMapping, body: &[u8]) -> Result<(Vec<FeedRow>, FeedSyncReport)> {
+    let records = match format {
+        FeedFormat::Csv => csv_records(body)?,
+        FeedFormat::Xml => xml_records(body, &mapping.record_element)?,
+        FeedFormat::Json => json_records(body, mapping.records_path.as_deref())?,
+    };
+
+    let mut report = FeedSyncReport {
+        rows: records.len(),
+        ..Default::default()
+    };
+    let mut rows = Vec::with_capacity(records.len());
+    for (index, record) in records.iter().enumerate() {
+        match to_row(record, mapping) {
+            Ok(row) => rows.push(row),
+            Err(e) => report.skip(format!("Row {}: {}", index + 1, e)),
+        }
+    }
+    Ok((rows, report))
+}
+
+/// Field names are matched case-insensitively
+type Record = HashMap<String, String>;
+
+fn field<'a>(record: &'a Record, name: &str) -> Option<&'a str> {
+    record
+        .get(&name.to_lowercase())
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+fn to_row(record: &Record, mapping: &FeedMapping) -> std::result::Result<FeedRow, String> {
+    let sku = field(record, &mapping.sku_field).ok_or_else(|| format!("no {}", mapping.sku_field))?;
+    let quantity = field(record, &mapping.quantity_field).ok_or_else(|| format!("no {}", mapping.quantity_field))?;
+    let quantity = Decimal::from_str(quantity)
+        .map_err(|_| format!("{} is not a number: {}", mapping.quantity_field, quantity))?
+        .trunc()
+        .max(Decimal::ZERO)
+        .min(Decimal::from(i32::MAX));
+    let cost = match mapping.cost_field.as_deref().and_then(|name| field(record, name)) {
+        Some(cost) => {
+            let cost = Decimal::from_str(cost.trim_start_matches('$'))
+                .map_err(|_| format!("cost is not a number: {}", cost))?;
+            if cost.is_sign_negative() {
+                return Err("cost is negative".to_string());
+            }
+            Some(cost)
+        }
+        None => None,
+    };
+    Ok(FeedRow {
+        sku: sku.to_string(),
+        quantity: quantity.try_into().unwrap_or_default(),
+        cost,
+    })
+}
+
+fn csv_records(body: &[u8]) -> Result<Vec<Record>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(body);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| Error::validation(format!("Invalid CSV feed: {}", e)))?
+        .iter()
+        .map(|header| header.trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::validation(format!("Invalid CSV feed: {}", e)))?;
+        records.push(
+            headers
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect(),
+        );
+    }
+    Ok(records)
+}
+
+fn xml_records(body: &[u8], record_element: &str) -> Result<Vec<Record>> {
+    let invalid = |e: quick_xml::Error| Error::validation(format!("Invalid XML feed: {}", e));
+    let mut reader = Reader::from_reader(body);
+    reader.trim_text(true);
+
+    let mut records = Vec::new();
+    let mut buf = Vec::new();
+    // The record being read, and the field (child element) within it
+    let mut record: Option<Record> = None;
+    let mut current_field: Option<String> = None;
+    let mut depth = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf).map_err(invalid)? {
+            Event::Start(ref e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase();
+                match record {
+                    None if name.eq_ignore_ascii_case(record_element) => {
+                        let mut fields = Record::new();
+                        for attribute in e.attributes().flatten() {
+                            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_lowercase();
+                            let value = attribute.unescape_value().map_err(invalid)?.into_owned();
+                            fields.insert(key, value);
+                        }
+                        record = Some(fields);
+                        depth = 0;
+                    }
+                    Some(_) => {
+                        depth += 1;
+                        if depth == 1 {
+                            current_field = Some(name);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Event::Empty(ref e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase();
+                if record.is_none() && name.eq_ignore_ascii_case(record_element) {
+                    let mut fields = Record::new();
+                    for attribute in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_lowercase();
+                        let value = attribute.unescape_value().map_err(invalid)?.into_owned();
+                        fields.insert(key, value);
+                    }
+                    records.push(fields);
+                }
+            }
+            Event::Text(ref e) => {
+                if let (Some(fields), Some(name)) = (record.as_mut(), current_field.as_ref()) {
+                    let text = e.unescape().map_err(invalid)?;
+                    fields.entry(name.clone()).or_default().push_str(&text);
+                }
+            }
+            Event::CData(ref e) => {
+                if let (Some(fields), Some(name)) = (record.as_mut(), current_field.as_ref()) {
+                    fields
+                        .entry(name.clone())
+                        .or_default()
+                        .push_str(&String::from_utf8_lossy(e.as_ref()));
+                }
+            }
+            Event::End(_) => match depth {
+                0 => {
+                    if let Some(fields) = record.take() {
+                        records.push(fields);
+                    }
+                }
+                _ => {
+                    depth -= 1;
+                    if depth == 0 {
+                        current_field = None;
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(records)
+}
+
+fn json_records(body: &[u8], records_path: Option<&str>) -> Result<Vec<Record>> {
+    let document: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| Error::validation(format!("Invalid JSON feed: {}", e)))?;
+    let mut value = &document;
+    for key in records_path.into_iter().flat_map(|path| path.split('.')).filter(|key| !key.is_empty()) {
+        value = value
+            .get(key)
+            .ok_or_else(|| Error::validation(format!("JSON feed has no {}", key)))?;
+    }
+    let items = value
+        .as_array()
+        .ok_or_else(|| Error::validation("JSON feed records are not an array"))?;
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            item.as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(value) => value.clone(),
+                        serde_json::Value::Number(value) => value.to_string(),
+                        serde_json::Value::Bool(value) => value.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.to_lowercase(), value))
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// What a feed's rows change about the supplier's products
+pub fn plan_updates(feed: &SupplierFeed, products: &[FeedProduct], rows: &[FeedRow], report: &mut FeedSyncReport) -> Vec<FeedUpdate> {
+    // Later rows for the same SKU win
+    let by_sku: HashMap<&str, &FeedRow> = rows.iter().map(|row| (row.sku.as_str(), row)).collect();
+    report.unmatched = by_sku
+        .keys()
+        .filter(|sku| !products.iter().any(|product| product.supplier_sku == **sku))
+        .count();
+
+    let mut updates = Vec::new();
+    for product in products {
+        let Some(row) = by_sku.get(product.supplier_sku.as_str()) else {
+            continue;
+        };
+        report.matched += 1;
+
+        let unit_cost = row.cost.or(product.unit_cost);
+        let price = match (feed.update_prices, row.cost) {
+            (true, Some(cost)) => apply_margin(&feed.margin_rules, cost).filter(|price| *price != product.price),
+            _ => None,
+        };
+        if price.is_some() {
+            report.prices_updated += 1;
+        }
+
+        let mut is_active = None;
+        let mut disabled_by_feed = product.disabled_by_feed;
+        if feed.auto_disable {
+            if row.quantity == 0 && product.is_active {
+                is_active = Some(false);
+                disabled_by_feed = true;
+                report.disabled += 1;
+            } else if row.quantity > 0 && !product.is_active && product.disabled_by_feed {
+                is_active = Some(true);
+                disabled_by_feed = false;
+                report.enabled += 1;
+            }
+        }
+        if product.is_active && row.quantity > 0 {
+            disabled_by_feed = false;
+        }
+
+        updates.push(FeedUpdate {
+            supplier_product_id: product.supplier_product_id,
+            product_id: product.product_id,
+            variant_id: product.variant_id,
+            supplier_stock: row.quantity,
+            unit_cost,
+            inventory_quantity: feed.dropship.then_some(row.quantity),
+            price,
+            is_active,
+            disabled_by_feed,
+        });
+    }
+    updates
+}
+
+/// Supplier feed and dropship service
+pub struct SupplierFeedService<R: SupplierFeedRepository> {
+    repository: R,
+    config: SupplierFeedsConfig,
+    http: reqwest::Client,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+}
+
+impl<R: SupplierFeedRepository> SupplierFeedService<R> {
+    pub fn new(repository: R, config: SupplierFeedsConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self {
+            repository,
+            config,
+            http,
+            notifications: None,
+        }
+    }
+
+    /// Email dropship orders to suppliers without an order webhook
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &SupplierFeedsConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Get a supplier's feed
+    pub async fn get_feed(&self, supplier_id: Uuid) -> Result<SupplierFeed> {
+        self.repository
+            .find_feed(supplier_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier feed not found"))
+    }
+
+    /// Create or replace a supplier's feed
+    pub async fn save_feed(&self, supplier_id: Uuid, request: SaveSupplierFeedRequest) -> Result<SupplierFeed> {
+        request.validate()?;
+        self.repository
+            .find_supplier(supplier_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))?;
+        let existing = self.repository.find_feed(supplier_id).await?;
+        let secret = match existing {
+            Some(feed) if !request.rotate_secret => feed.webhook_secret,
+            _ => generate_secret(),
+        };
+        self.repository.save_feed(supplier_id, &request, &secret).await
+    }
+
+    /// Remove a supplier's feed
+    pub async fn delete_feed(&self, supplier_id: Uuid) -> Result<()> {
+        if !self.repository.delete_feed(supplier_id).await? {
+            return Err(Error::not_found("Supplier feed not found"));
+        }
+        Ok(())
+    }
+
+    /// Pull a supplier's feed now
+    pub async fn sync(&self, supplier_id: Uuid) -> Result<FeedSyncReport> {
+        let feed = self.get_feed(supplier_id).await?;
+        self.pull(&feed).await
+    }
+
+    /// Apply a feed the supplier pushed, after checking its signature
+    pub async fn ingest_signed(&self, supplier_id: Uuid, body: &[u8], signature: Option<&str>) -> Result<FeedSyncReport> {
+        let feed = self
+            .repository
+            .find_feed(supplier_id)
+            .await?
+            .filter(|feed| feed.is_active)
+            .ok_or_else(|| Error::not_found("Supplier feed not found"))?;
+        if !signature.is_some_and(|signature| verify_signature(&feed.webhook_secret, body, signature)) {
+            return Err(Error::unauthorized("Invalid feed signature"));
+        }
+        self.ingest(&feed, body).await
+    }
+
+    /// Apply a feed file uploaded by staff
+    pub async fn upload(&self, supplier_id: Uuid, body: &[u8]) -> Result<FeedSyncReport> {
+        let feed = self.get_feed(supplier_id).await?;
+        self.ingest(&feed, body).await
+    }
+
+    /// Pull every active feed that is due
+    pub async fn sync_due(&self) -> Result<FeedRunReport> {
+        let mut report = FeedRunReport::default();
+        for feed in self.repository.due_feeds().await? {
+            match self.pull(&feed).await {
+                Ok(sync) => {
+                    tracing::info!("Synced supplier feed of {}: {}", feed.supplier_id, sync);
+                    report.synced += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to sync supplier feed of {}: {}", feed.supplier_id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn pull(&self, feed: &SupplierFeed) -> Result<FeedSyncReport> {
+        let Some(ref url) = feed.source_url else {
+            return Err(Error::validation("The feed has no source_url; it can only be pushed"));
+        };
+        let body = match self.download(url).await {
+            Ok(body) => body,
+            Err(e) => {
+                self.repository.record_sync(feed.id, None, Some(&e.to_string())).await?;
+                return Err(e);
+            }
+        };
+        self.ingest(feed, &body).await
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to download supplier feed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(Error::Other(format!("Supplier feed download returned {}", response.status())));
+        }
+        if response.content_length().is_some_and(|length| length > self.config.max_feed_bytes as u64) {
+            return Err(Error::validation("Supplier feed is too large"));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to download supplier feed: {}", e)))?;
+        Ok(body.to_vec())
+    }
+
+    async fn ingest(&self, feed: &SupplierFeed, body: &[u8]) -> Result<FeedSyncReport> {
+        if body.len() > self.config.max_feed_bytes {
+            return Err(Error::validation(format!(
+                "Supplier feeds are limited to {} bytes",
+                self.config.max_feed_bytes
+            )));
+        }
+        let (rows, mut report) = match parse_feed(feed.format, &feed.mapping, body) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.repository.record_sync(feed.id, None, Some(&e.to_string())).await?;
+                return Err(e);
+            }
+        };
+
+        let products = self.repository.feed_products(feed.supplier_id).await?;
+        let updates = plan_updates(feed, &products, &rows, &mut report);
+        self.repository.apply_updates(&updates).await?;
+        self.repository.record_sync(feed.id, Some(&report), None).await?;
+        Ok(report)
+    }
+
+    /// Dropship orders, newest first
+    pub async fn list_dropship_orders(
+        &self,
+        status: Option<DropshipStatus>,
+        supplier_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<DropshipOrder>> {
+        self.repository
+            .list_dropship_orders(status, supplier_id, limit.clamp(1, MAX_LIST_LIMIT))
+            .await
+    }
+
+    /// Get a dropship order with its lines
+    pub async fn get_dropship_order(&self, id: Uuid) -> Result<DropshipOrderDetail> {
+        self.repository
+            .dropship_order_detail(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Dropship order not found"))
+    }
+
+    /// Route paid orders and send what is pending (or failed and retriable)
+    pub async fn process_dropship(&self) -> Result<DropshipReport> {
+        let mut report = DropshipReport {
+            routed: self.route_orders().await?.len(),
+            ..Default::default()
+        };
+        for order in self.repository.sendable_dropship_orders(self.config.max_send_attempts).await? {
+            match self.send(order.id).await {
+                Ok(_) => report.sent += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to send dropship order {}: {}", order.id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Group unrouted paid order lines into dropship orders; returns their IDs
+    pub async fn route_orders(&self) -> Result<Vec<Uuid>> {
+        let lines = self.repository.unrouted_lines().await?;
+        let mut groups: BTreeMap<(Uuid, Uuid), Vec<DropshipLine>> = BTreeMap::new();
+        for line in lines {
+            groups.entry((line.order_id, line.supplier_id)).or_default().push(line);
+        }
+
+        let mut routed = Vec::with_capacity(groups.len());
+        for ((order_id, supplier_id), lines) in groups {
+            routed.push(self.repository.create_dropship_order(order_id, supplier_id, &lines).await?);
+        }
+        Ok(routed)
+    }
+
+    /// Send a dropship order that was not sent yet
+    pub async fn retry(&self, id: Uuid) -> Result<DropshipOrderDetail> {
+        let detail = self.get_dropship_order(id).await?;
+        if detail.dropship_order.status == DropshipStatus::Sent {
+            return Err(Error::validation("Dropship order was already sent"));
+        }
+        // A failed send is recorded on the order, which is returned either way
+        if let Err(e) = self.send(id).await {
+            tracing::warn!("Failed to send dropship order {}: {}", id, e);
+        }
+        self.get_dropship_order(id).await
+    }
+
+    async fn send(&self, id: Uuid) -> Result<()> {
+        let detail = self.get_dropship_order(id).await?;
+        let result = self.deliver(&detail).await;
+        match result {
+            Ok(reference) => self.repository.mark_dropship_sent(id, reference.as_deref()).await,
+            Err(e) => {
+                self.repository.mark_dropship_failed(id, &e.to_string()).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// POST the order to the supplier's webhook, or email it; returns the
+    /// supplier's reference
+    async fn deliver(&self, detail: &DropshipOrderDetail) -> Result<Option<String>> {
+        let supplier_id = detail.dropship_order.supplier_id;
+        let feed = self
+            .repository
+            .find_feed(supplier_id)
+            .await?
+            .ok_or_else(|| Error::validation("The supplier has no feed"))?;
+
+        if let Some(ref url) = feed.order_webhook_url {
+            let body = serde_json::to_vec(&detail.payload())?;
+            let response = self
+                .http
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header(SIGNATURE_HEADER, format!("sha256={}", sign(&feed.webhook_secret, &body)))
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| Error::Other(format!("Failed to send dropship order: {}", e)))?;
+            if !response.status().is_success() {
+                return Err(Error::Other(format!("Supplier order webhook returned {}", response.status())));
+            }
+            let reply: serde_json::Value = response.json().await.unwrap_or_default();
+            return Ok(reply
+                .get("reference")
+                .or_else(|| reply.get("id"))
+                .and_then(|reference| match reference {
+                    serde_json::Value::String(reference) => Some(reference.clone()),
+                    serde_json::Value::Number(reference) => Some(reference.to_string()),
+                    _ => None,
+                })
+                .map(|reference| reference.chars().take(100).collect()));
+        }
+
+        let supplier = self
+            .repository
+            .find_supplier(supplier_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Supplier not found"))?;
+        let (Some(email), Some(notifications)) = (supplier.email.clone(), self.notifications.as_ref()) else {
+            return Err(Error::validation("The supplier has no order webhook or email"));
+        };
+        let notification = NotificationFactory::dropship_order(detail, &supplier.name, Recipient::email(email, None));
+        notifications.create(&notification).await?;
+        Ok(None)
+    }
+}
+
+/// A new webhook secret
+fn generate_secret() -> String {
+    use rand::Rng;
+
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect()
+}
+
+/// The `supplier_feeds` recurring job
+pub struct SupplierFeedJob<R: SupplierFeedRepository> {
+    feeds: Arc<SupplierFeedService<R>>,
+}
+
+impl<R: SupplierFeedRepository> SupplierFeedJob<R> {
+    pub fn new(feeds: Arc<SupplierFeedService<R>>) -> Self {
+        Self { feeds }
+    }
+}
+
+#[async_trait]
+impl<R: SupplierFeedRepository + 'static> RecurringJob for SupplierFeedJob<R> {
+    fn name(&self) -> &str {
+        "supplier_feeds"
+    }
+
+    fn description(&self) -> &str {
+        "Pull supplier stock and price feeds that are due"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.feeds.config().feed_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.feeds.sync_due().await?.to_string())
+    }
+}
+
+/// The `dropship` recurring job
+pub struct DropshipJob<R: SupplierFeedRepository> {
+    feeds: Arc<SupplierFeedService<R>>,
+}
+
+impl<R: SupplierFeedRepository> DropshipJob<R> {
+    pub fn new(feeds: Arc<SupplierFeedService<R>>) -> Self {
+        Self { feeds }
+    }
+}
+
+#[async_trait]
+impl<R: SupplierFeedRepository + 'static> RecurringJob for DropshipJob<R> {
+    fn name(&self) -> &str {
+        "dropship"
+    }
+
+    fn description(&self) -> &str {
+        "Route paid orders of dropshipped products to their suppliers"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.feeds.config().dropship_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.feeds.process_dropship().await?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn feed() -> SupplierFeed {
+        SupplierFeed {
+            id: Uuid::new_v4(),
+            supplier_id: Uuid::new_v4(),
+            format: FeedFormat::Csv,
+            source_url: None,
+            pull_interval_minutes: 60,
+            webhook_secret: "secret".to_string(),
+            mapping: Json(FeedMapping::default()),
+            margin_rules: Json(vec![MarginRule {
+                min_cost: None,
+                max_cost: None,
+                markup_percent: dec!(50),
+                fixed_amount: Decimal::ZERO,
+                price_ending: None,
+            }]),
+            update_prices: true,
+            auto_disable: true,
+            dropship: true,
+            order_webhook_url: None,
+            is_active: true,
+            last_synced_at: None,
+            last_error: None,
+            last_report: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn product(sku: &str, is_active: bool, disabled_by_feed: bool) -> FeedProduct {
+        FeedProduct {
+            supplier_product_id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            supplier_sku: sku.to_string(),
+            unit_cost: Some(dec!(8.00)),
+            disabled_by_feed,
+            is_active,
+            price: dec!(12.00),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_feed() {
+        let body = b"SKU,Quantity,Cost\nA-1,12,4.50\nA-2,-3,\n,5,1.00\nA-3,lots,2.00\n";
+        let (rows, report) = parse_feed(FeedFormat::Csv, &FeedMapping::default(), body).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                FeedRow { sku: "A-1".to_string(), quantity: 12, cost: Some(dec!(4.50)) },
+                FeedRow { sku: "A-2".to_string(), quantity: 0, cost: None },
+            ]
+        );
+        assert_eq!(report.rows, 4);
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_json_and_xml_feeds() {
+        let mapping = FeedMapping {
+            sku_field: "code".to_string(),
+            quantity_field: "stock".to_string(),
+            cost_field: Some("price".to_string()),
+            record_element: "product".to_string(),
+            records_path: Some("data.products".to_string()),
+        };
+
+        let json = br#"{"data":{"products":[{"code":"B-1","stock":7,"price":"3.25"},{"code":"B-2","stock":"0"}]}}"#;
+        let (rows, _) = parse_feed(FeedFormat::Json, &mapping, json).unwrap();
+        assert_eq!(rows[0], FeedRow { sku: "B-1".to_string(), quantity: 7, cost: Some(dec!(3.25)) });
+        assert_eq!(rows[1].quantity, 0);
+        assert!(parse_feed(FeedFormat::Json, &FeedMapping::default(), json).is_err());
+
+        let xml = br#"<?xml version="1.0"?>
+            <catalog>
+              <product code="B-1"><stock>7</stock><price><![CDATA[3.25]]></price></product>
+              <product code="B-2" stock="4"/>
+            </catalog>"#;
+        let (rows, report) = parse_feed(FeedFormat::Xml, &mapping, xml).unwrap();
+        assert_eq!(report.skipped, 0);
+        assert_eq!(rows[0], FeedRow { sku: "B-1".to_string(), quantity: 7, cost: Some(dec!(3.25)) });
+        assert_eq!(rows[1], FeedRow { sku: "B-2".to_string(), quantity: 4, cost: None });
+    }
+
+    #[test]
+    fn test_margin_rules() {
+        let rules = vec![
+            MarginRule {
+                min_cost: None,
+                max_cost: Some(dec!(10)),
+                markup_percent: dec!(100),
+                fixed_amount: Decimal::ZERO,
+                price_ending: Some(dec!(0.99)),
+            },
+            MarginRule {
+                min_cost: Some(dec!(10)),
+                max_cost: None,
+                markup_percent: dec!(40),
+                fixed_amount: dec!(2),
+                price_ending: None,
+            },
+        ];
+        assert_eq!(apply_margin(&rules, dec!(4.20)), Some(dec!(8.99)));
+        // 9.995 × 2 rounds to 19.99
+        assert_eq!(apply_margin(&rules, dec!(9.995)), Some(dec!(19.99)));
+        assert_eq!(apply_margin(&rules, dec!(10)), Some(dec!(16.00)));
+        assert_eq!(apply_margin(&rules[1..], dec!(5)), None);
+
+        let mut request = SaveSupplierFeedRequest {
+            format: FeedFormat::Csv,
+            source_url: Some("https://supplier.example/feed.csv".to_string()),
+            pull_interval_minutes: 60,
+            mapping: FeedMapping::default(),
+            margin_rules: rules,
+            update_prices: true,
+            auto_disable: true,
+            dropship: false,
+            order_webhook_url: None,
+            is_active: true,
+            rotate_secret: false,
+        };
+        assert!(request.validate().is_ok());
+        request.margin_rules[0].price_ending = Some(dec!(1.5));
+        assert!(request.validate().is_err());
+        request.margin_rules.clear();
+        assert!(request.validate().is_err());
+        request.update_prices = false;
+        request.source_url = Some("ftp://supplier.example/feed.csv".to_string());
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_plan_updates() {
+        let feed = feed();
+        let products = vec![
+            product("A-1", true, false),
+            product("A-2", true, false),
+            product("A-3", false, true),
+            product("A-4", false, false),
+        ];
+        let rows = vec![
+            FeedRow { sku: "A-1".to_string(), quantity: 5, cost: Some(dec!(10.00)) },
+            FeedRow { sku: "A-2".to_string(), quantity: 0, cost: Some(dec!(8.00)) },
+            FeedRow { sku: "A-3".to_string(), quantity: 2, cost: None },
+            FeedRow { sku: "A-4".to_string(), quantity: 9, cost: None },
+            FeedRow { sku: "X-9".to_string(), quantity: 1, cost: None },
+        ];
+        let mut report = FeedSyncReport::default();
+        let updates = plan_updates(&feed, &products, &rows, &mut report);
+
+        assert_eq!(report.matched, 4);
+        assert_eq!(report.unmatched, 1);
+        assert_eq!((report.disabled, report.enabled), (1, 1));
+        // 10.00 × 1.5 changes the price; 8.00 × 1.5 is already 12.00
+        assert_eq!(report.prices_updated, 1);
+        assert_eq!(updates[0].price, Some(dec!(15.00)));
+        assert_eq!(updates[0].inventory_quantity, Some(5));
+        assert_eq!((updates[1].is_active, updates[1].disabled_by_feed), (Some(false), true));
+        assert_eq!((updates[2].is_active, updates[2].disabled_by_feed), (Some(true), false));
+        assert_eq!(updates[2].unit_cost, Some(dec!(8.00)));
+        // Disabled by staff, so restocking leaves it disabled
+        assert_eq!(updates[3].is_active, None);
+    }
+
+    #[test]
+    fn test_signature() {
+        let body = b"sku,quantity\nA-1,3\n";
+        let signature = format!("sha256={}", sign("secret", body));
+        assert!(verify_signature("secret", body, &signature));
+        assert!(verify_signature("secret", body, signature.trim_start_matches("sha256=")));
+        assert!(!verify_signature("other", body, &signature));
+        assert!(!verify_signature("secret", b"sku,quantity\nA-1,300\n", &signature));
+        assert!(!verify_signature("secret", body, "sha256=not-hex"));
+    }
+}
@@ -121,6 +121,7 @@ pub enum StockMovementType {
     Lost,    // Stock lost/damaged
     Found,   // Stock found
     Transfer, // Stock transferred
+    Adjustment, // Manual correction
 }
 
 /// Stock adjustment (manual correction)
@@ -1,12 +1,39 @@
 //! Dead letter queue for failed jobs that exhausted all retry attempts
+//!
+//! Recurring jobs that fail every attempt of their retry policy are kept in
+//! `job_dead_letters` with each attempt's error. [`retry`] asks for a
+//! one-off run of the job on the next scheduler poll; [`discard`] drops the
+//! entry. Both only act on entries still awaiting action.
 
 use crate::jobs::job::Job;
 use crate::jobs::{JobError, RetryHistory};
+use crate::models::{JobDeadLetter, DEAD_LETTER_DISCARDED, DEAD_LETTER_RETRIED};
+use crate::repository::ScheduledJobRepository;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use uuid::Uuid;
 
+/// Run a dead-lettered job again on the next scheduler poll
+pub async fn retry<R: ScheduledJobRepository + ?Sized>(repository: &R, id: Uuid) -> crate::Result<JobDeadLetter> {
+    resolve(repository, id, DEAD_LETTER_RETRIED).await
+}
+
+/// Drop a dead-lettered job without running it again
+pub async fn discard<R: ScheduledJobRepository + ?Sized>(repository: &R, id: Uuid) -> crate::Result<JobDeadLetter> {
+    resolve(repository, id, DEAD_LETTER_DISCARDED).await
+}
+
+async fn resolve<R: ScheduledJobRepository + ?Sized>(repository: &R, id: Uuid, status: &str) -> crate::Result<JobDeadLetter> {
+    if let Some(entry) = repository.resolve_dead_letter(id, status).await? {
+        return Ok(entry);
+    }
+    match repository.find_dead_letter(id).await? {
+        Some(entry) => Err(crate::Error::validation(format!("Failed job {} was already {}", id, entry.status))),
+        None => Err(crate::Error::not_found(format!("Failed job {} not found", id))),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeadLetter {
     pub id: Uuid,
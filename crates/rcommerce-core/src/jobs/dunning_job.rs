@@ -358,6 +358,10 @@ mod tests {
             Ok(())
         }
 
+        async fn schedule_invoice_retry(&self, _invoice_id: Uuid, _next_retry_at: chrono::DateTime<Utc>) -> Result<()> {
+            Ok(())
+        }
+
         async fn get_pending_invoices(&self) -> Result<Vec<SubscriptionInvoice>> {
             Ok(vec![])
         }
@@ -0,0 +1,387 @@
+//! Distributed locks and leader election
+//!
+//! Background tasks that must run on one instance at a time (order
+//! archiving, partition maintenance, gift card expiry, purges) take a named
+//! lock first. With Postgres, the default, a lock is a session advisory lock
+//! on a connection kept for as long as the lock is held: if the instance
+//! dies, its connection drops and the lock with it. With Redis it is a key
+//! set with NX and a TTL, refreshed while held. [`LeaderElector`] keeps
+//! campaigning for a lock and tells whether this instance leads;
+//! [`spawn_singleton`] runs a periodic task on the leader only. Recurring
+//! jobs elect their instance per run with leases instead (see `recurring`).
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+use tracing::{debug, info, warn};
+
+use crate::cache::RedisPool;
+use crate::config::{JobSchedulerConfig, LockBackend};
+use crate::{Error, Result};
+
+/// Takes named locks shared by every instance
+#[async_trait]
+pub trait DistributedLock: Send + Sync {
+    /// Take the lock `name`; None if another instance holds it
+    async fn try_lock(&self, name: &str) -> Result<Option<Box<dyn LockGuard>>>;
+}
+
+/// A held lock; dropping it releases it (or, with Redis, lets it expire)
+#[async_trait]
+pub trait LockGuard: Send {
+    fn name(&self) -> &str;
+
+    /// Whether the lock is still held, extending it where locks expire
+    async fn refresh(&mut self) -> Result<bool>;
+
+    /// Release the lock now
+    async fn release(self: Box<Self>) -> Result<()>;
+}
+
+/// The locks configured in `[scheduler]`; Postgres when Redis locks are
+/// asked for but Redis is not configured
+pub fn locks_from_config(
+    config: &JobSchedulerConfig,
+    db: PgPool,
+    redis: Option<RedisPool>,
+) -> Arc<dyn DistributedLock> {
+    match (config.locks, redis) {
+        (LockBackend::Redis, Some(redis)) => Arc::new(RedisLocks::new(
+            redis,
+            config.instance_id.clone().unwrap_or_else(super::recurring::default_instance_id),
+            Duration::from_secs(config.lock_ttl_secs),
+        )),
+        (LockBackend::Redis, None) => {
+            warn!("scheduler.locks is redis but Redis is not configured; using Postgres advisory locks");
+            Arc::new(PostgresLocks::new(db))
+        }
+        (LockBackend::Postgres, _) => Arc::new(PostgresLocks::new(db)),
+    }
+}
+
+/// Advisory lock key of a lock name
+fn advisory_key(name: &str) -> i64 {
+    let digest = Sha256::digest(format!("rcommerce:{}", name).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+/// Session advisory locks
+pub struct PostgresLocks {
+    db: PgPool,
+}
+
+impl PostgresLocks {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DistributedLock for PostgresLocks {
+    async fn try_lock(&self, name: &str) -> Result<Option<Box<dyn LockGuard>>> {
+        let mut conn = self
+            .db
+            .acquire()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get a connection for lock {}: {}", name, e)))?;
+        let key = advisory_key(name);
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(key)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to take lock {}: {}", name, e)))?;
+        if !locked {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(PostgresLockGuard {
+            name: name.to_string(),
+            key,
+            conn: Some(conn),
+        })))
+    }
+}
+
+/// An advisory lock and the connection holding it
+struct PostgresLockGuard {
+    name: String,
+    key: i64,
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+#[async_trait]
+impl LockGuard for PostgresLockGuard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn refresh(&mut self) -> Result<bool> {
+        let Some(conn) = self.conn.as_mut() else {
+            return Ok(false);
+        };
+        // The lock lives as long as the session does
+        match sqlx::query("SELECT 1").execute(&mut **conn).await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                warn!("Lost the connection holding lock {}: {}", self.name, e);
+                if let Some(conn) = self.conn.take() {
+                    drop(conn.detach());
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    async fn release(mut self: Box<Self>) -> Result<()> {
+        let Some(mut conn) = self.conn.take() else {
+            return Ok(());
+        };
+        let result = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(self.key)
+            .execute(&mut *conn)
+            .await;
+        if let Err(e) = result {
+            // Close the session rather than pool a connection that may hold the lock
+            drop(conn.detach());
+            return Err(Error::Other(format!("Failed to release lock {}: {}", self.name, e)));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PostgresLockGuard {
+    fn drop(&mut self) {
+        // Not released: close the session, which releases the lock
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
+
+/// Keys set with NX and a TTL
+pub struct RedisLocks {
+    redis: RedisPool,
+    holder: String,
+    ttl: Duration,
+}
+
+impl RedisLocks {
+    /// Locks held in the name of `holder` (this instance), expiring `ttl`
+    /// after their last refresh
+    pub fn new(redis: RedisPool, holder: String, ttl: Duration) -> Self {
+        Self { redis, holder, ttl }
+    }
+}
+
+/// Extend the lock if this holder still has it
+const REFRESH_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Delete the lock if this holder still has it
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+#[async_trait]
+impl DistributedLock for RedisLocks {
+    async fn try_lock(&self, name: &str) -> Result<Option<Box<dyn LockGuard>>> {
+        let key = format!("rcommerce:lock:{}", name);
+        // A token per acquisition, so a holder that lost its lock can't
+        // refresh or release the next holder's
+        let token = format!("{}:{}", self.holder, uuid::Uuid::new_v4().simple());
+        let mut cmd = redis::Cmd::new();
+        cmd.arg("SET")
+            .arg(&key)
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl.as_millis() as u64);
+        let conn = self.redis.get().await?;
+        let reply = conn.execute(cmd).await?;
+        if !matches!(reply, redis::Value::Okay) {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(RedisLockGuard {
+            name: name.to_string(),
+            key,
+            token,
+            redis: self.redis.clone(),
+            ttl: self.ttl,
+        })))
+    }
+}
+
+struct RedisLockGuard {
+    name: String,
+    key: String,
+    token: String,
+    redis: RedisPool,
+    ttl: Duration,
+}
+
+#[async_trait]
+impl LockGuard for RedisLockGuard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn refresh(&mut self) -> Result<bool> {
+        let conn = self.redis.get().await?;
+        let reply = conn
+            .eval(
+                REFRESH_SCRIPT,
+                std::slice::from_ref(&self.key),
+                &[self.token.clone(), (self.ttl.as_millis() as u64).to_string()],
+            )
+            .await?;
+        Ok(matches!(reply, redis::Value::Int(1)))
+    }
+
+    async fn release(self: Box<Self>) -> Result<()> {
+        let conn = self.redis.get().await?;
+        conn.eval(
+            RELEASE_SCRIPT,
+            std::slice::from_ref(&self.key),
+            std::slice::from_ref(&self.token),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Campaigns for a lock in the background for as long as it lives; the
+/// instance holding the lock is the leader
+pub struct LeaderElector {
+    name: String,
+    leader: Arc<AtomicBool>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LeaderElector {
+    /// Start campaigning for `name`, trying for (and, once leader,
+    /// re-checking) the lock every `check_interval`
+    pub fn spawn(locks: Arc<dyn DistributedLock>, name: &str, check_interval: Duration) -> Self {
+        let leader = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn(campaign(locks, name.to_string(), check_interval, leader.clone()));
+        Self {
+            name: name.to_string(),
+            leader,
+            task,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this instance leads now
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for LeaderElector {
+    fn drop(&mut self) {
+        // Dropping the campaign drops its guard, giving up the lock
+        self.task.abort();
+    }
+}
+
+async fn campaign(locks: Arc<dyn DistributedLock>, name: String, check_interval: Duration, leader: Arc<AtomicBool>) {
+    let mut guard: Option<Box<dyn LockGuard>> = None;
+    let mut ticker = tokio::time::interval(check_interval);
+    loop {
+        ticker.tick().await;
+        match guard.as_mut() {
+            Some(held) => {
+                let still_held = held.refresh().await.unwrap_or_else(|e| {
+                    warn!("Failed to refresh lock {}: {}", name, e);
+                    false
+                });
+                if !still_held {
+                    leader.store(false, Ordering::Release);
+                    guard = None;
+                    warn!("Lost leadership of {}", name);
+                }
+            }
+            None => match locks.try_lock(&name).await {
+                Ok(Some(held)) => {
+                    guard = Some(held);
+                    leader.store(true, Ordering::Release);
+                    info!("This instance now leads {}", name);
+                }
+                Ok(None) => debug!("Another instance leads {}", name),
+                Err(e) => warn!("Failed to campaign for {}: {}", name, e),
+            },
+        }
+    }
+}
+
+/// Run `task` every `interval` on the one instance leading `name`
+pub fn spawn_singleton<F, Fut>(
+    locks: Arc<dyn DistributedLock>,
+    name: &str,
+    interval: Duration,
+    task: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    // Re-check leadership more often than the task runs, so a lost leader
+    // is replaced before the next run
+    let check_interval = (interval / 3).clamp(Duration::from_secs(5), Duration::from_secs(30));
+    let elector = LeaderElector::spawn(locks, name, check_interval);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick is immediate; give the election a moment
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        loop {
+            ticker.tick().await;
+            if elector.is_leader() {
+                task().await;
+            } else {
+                debug!("Skipping {}: another instance leads it", elector.name());
+            }
+        }
+    })
+}
+
+/// Run `task` once if this instance gets the lock `name`; returns whether it ran
+pub async fn run_once<Fut>(locks: &dyn DistributedLock, name: &str, task: Fut) -> Result<bool>
+where
+    Fut: Future<Output = ()>,
+{
+    let Some(guard) = locks.try_lock(name).await? else {
+        info!("Skipping {}: another instance is running it", name);
+        return Ok(false);
+    };
+    task.await;
+    guard.release().await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advisory_key() {
+        assert_eq!(advisory_key("order_archive"), advisory_key("order_archive"));
+        assert_ne!(advisory_key("order_archive"), advisory_key("gift_card_expiry"));
+    }
+}
@@ -39,6 +39,9 @@
 //!
 //! ✅ **Scheduling**
 //! - Cron-like scheduling
+//! - Recurring jobs with schedules in the database, one instance per run
+//!   (`recurring`)
+//! - Distributed locks and leader election for singleton tasks (`lock`)
 //! - One-time scheduled jobs
 //! - Recurring jobs
 //! - Timezone support
@@ -59,6 +62,8 @@ pub mod retry;
 pub mod metrics;
 pub mod dead_letter;
 pub mod dunning_job;
+pub mod recurring;
+pub mod lock;
 
 // Re-export main types
 pub use config::{JobConfig, WorkerConfig, SchedulerConfig};
@@ -70,6 +75,8 @@ pub use retry::{RetryPolicy, ExponentialBackoff, RetryHistory, RetryAttempt};
 pub use metrics::{JobMetrics, MetricsSummary};
 pub use dead_letter::{DeadLetterQueue, DeadLetter};
 pub use dunning_job::{DunningJob, DunningJobResult, DunningJobStats, DunningJobScheduler};
+pub use recurring::{RecurringJob, RecurringScheduler};
+pub use lock::{locks_from_config, spawn_singleton, DistributedLock, LeaderElector, LockGuard, PostgresLocks, RedisLocks};
 // JobError is defined in this module and re-exported automatically
 
 /// Job processing result type
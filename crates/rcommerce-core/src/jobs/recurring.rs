@@ -0,0 +1,403 @@
+//! Recurring jobs on cron schedules
+//!
+//! Each instance registers the jobs it can run with a `RecurringScheduler`.
+//! Schedules live in `scheduled_jobs`, so they (and pausing) survive
+//! restarts and apply to every instance. On each poll the scheduler tries
+//! to take the lease of every due job; the instance that gets it runs the
+//! job, records the outcome and computes the next run from the schedule.
+//! An instance that dies mid-run loses the job once its lease runs out.
+//! At most `scheduler.concurrency` jobs run at once per instance; a
+//! stopping instance stops taking jobs and drains the running ones.
+//!
+//! A failed run is retried with exponential backoff per the job's
+//! `scheduler.retry_policies` entry (or `scheduler.retry`), ahead of its
+//! regular schedule. Once the policy gives up, the failures move to the
+//! dead-letter queue (`job_dead_letters`) and the job carries on with its
+//! schedule.
+//!
+//! Jobs are managed with `rcommerce jobs list|run|pause|resume|schedule`,
+//! and dead letters with `rcommerce jobs failed|retry|discard`.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, warn};
+
+use crate::config::JobSchedulerConfig;
+use crate::models::{JobFailure, ScheduledJob, JOB_FAILED, JOB_SUCCEEDED};
+use crate::repository::ScheduledJobRepository;
+use crate::{Error, Result};
+
+/// A job run on a cron schedule
+#[async_trait]
+pub trait RecurringJob: Send + Sync {
+    /// Unique name, as used by `rcommerce jobs`
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// Schedule of a job registered for the first time
+    fn default_schedule(&self) -> String;
+
+    /// Run once; the summary is recorded with the run
+    async fn run(&self) -> Result<String>;
+}
+
+/// Parse a cron expression
+///
+/// Takes the usual five fields (minute, hour, day of month, month, day of
+/// week) or six or seven with seconds first and an optional year. Day of
+/// week numbers count from 1 = Sunday, so names (`MON-FRI`) are clearer.
+pub fn parse_schedule(expression: &str) -> Result<cron::Schedule> {
+    let expression = expression.trim();
+    let full = match expression.split_whitespace().count() {
+        5 => format!("0 {}", expression),
+        6 | 7 => expression.to_string(),
+        _ => {
+            return Err(Error::validation(format!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expression
+            )))
+        }
+    };
+    cron::Schedule::from_str(&full)
+        .map_err(|e| Error::validation(format!("Invalid cron expression '{}': {}", expression, e)))
+}
+
+/// First time a schedule fires after `after`
+pub fn next_run(expression: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    parse_schedule(expression)?
+        .after(&after)
+        .next()
+        .ok_or_else(|| Error::validation(format!("Cron expression '{}' never fires again", expression)))
+}
+
+/// A schedule running about every `secs` seconds, rounded down to an
+/// interval that divides the hour (or the day)
+pub fn interval_schedule(secs: u64) -> String {
+    let minutes = (secs / 60).max(1);
+    if minutes < 60 {
+        let step = (1..=minutes).rev().find(|m| 60 % m == 0).unwrap_or(1);
+        return if step == 1 { "* * * * *".to_string() } else { format!("*/{} * * * *", step) };
+    }
+
+    let hours = minutes / 60;
+    if hours >= 24 {
+        return "0 0 * * *".to_string();
+    }
+    let step = (1..=hours).rev().find(|h| 24 % h == 0).unwrap_or(1);
+    if step == 1 { "0 * * * *".to_string() } else { format!("0 */{} * * *", step) }
+}
+
+/// Host name and process id of this instance
+pub(crate) fn default_instance_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "rcommerce".to_string());
+    format!("{}:{}", host, std::process::id())
+}
+
+/// Runs registered recurring jobs when they are due
+pub struct RecurringScheduler<R: ScheduledJobRepository> {
+    repository: R,
+    config: JobSchedulerConfig,
+    instance_id: String,
+    jobs: BTreeMap<String, Arc<dyn RecurringJob>>,
+    /// One permit per job that may run at once
+    slots: Arc<Semaphore>,
+}
+
+impl<R: ScheduledJobRepository + 'static> RecurringScheduler<R> {
+    pub fn new(repository: R, config: JobSchedulerConfig) -> Self {
+        let instance_id = config.instance_id.clone().unwrap_or_else(default_instance_id);
+        let slots = Arc::new(Semaphore::new(config.concurrency.max(1)));
+        Self {
+            repository,
+            config,
+            instance_id,
+            jobs: BTreeMap::new(),
+            slots,
+        }
+    }
+
+    /// Name this instance records on the leases it takes
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Add a job this instance can run
+    pub fn register(&mut self, job: Arc<dyn RecurringJob>) {
+        if self.jobs.insert(job.name().to_string(), job.clone()).is_some() {
+            warn!("Recurring job {} registered twice; keeping the last one", job.name());
+        }
+    }
+
+    /// Names of the registered jobs
+    pub fn job_names(&self) -> Vec<&str> {
+        self.jobs.keys().map(String::as_str).collect()
+    }
+
+    /// Add registered jobs that are new to the table
+    pub async fn sync(&self) -> Result<()> {
+        let now = Utc::now();
+        for job in self.jobs.values() {
+            let schedule = job.default_schedule();
+            let next_run_at = next_run(&schedule, now)?;
+            let row = self
+                .repository
+                .register(job.name(), job.description(), &schedule, next_run_at)
+                .await?;
+            info!(
+                "Recurring job {} scheduled '{}'{}, next run {}",
+                row.name,
+                row.schedule,
+                if row.paused { " (paused)" } else { "" },
+                row.next_run_at
+            );
+        }
+        Ok(())
+    }
+
+    /// Jobs running on this instance now
+    pub fn running(&self) -> usize {
+        self.config.concurrency.max(1) - self.slots.available_permits()
+    }
+
+    /// Start every registered job that is due and whose lease this
+    /// instance gets, while it has free slots; returns how many were started
+    pub async fn run_due(self: &Arc<Self>) -> usize {
+        let mut started = 0;
+        for (name, job) in &self.jobs {
+            // Take a slot before the lease, so a busy instance leaves the
+            // job to others
+            let Ok(slot) = self.slots.clone().try_acquire_owned() else {
+                debug!("All {} job slots busy; due jobs wait for the next poll", self.config.concurrency);
+                break;
+            };
+            let row = match self.repository.acquire(name, &self.instance_id, self.config.lease_secs).await {
+                Ok(Some(row)) => row,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to check recurring job {}: {}", name, e);
+                    continue;
+                }
+            };
+
+            started += 1;
+            let scheduler = self.clone();
+            let job = job.clone();
+            tokio::spawn(async move {
+                scheduler.run_job(job, row).await;
+                drop(slot);
+            });
+        }
+        started
+    }
+
+    async fn run_job(&self, job: Arc<dyn RecurringJob>, row: ScheduledJob) {
+        info!("Running recurring job {}{}", row.name, if row.run_requested { " (requested)" } else { "" });
+        let started = Instant::now();
+        let result = job.run().await;
+        let duration_ms = started.elapsed().as_millis() as i64;
+
+        let (status, message, failures) = match result {
+            Ok(summary) => {
+                info!("Recurring job {} finished in {}ms: {}", row.name, duration_ms, summary);
+                (JOB_SUCCEEDED, summary, Vec::new())
+            }
+            Err(e) => {
+                error!("Recurring job {} failed after {}ms: {}", row.name, duration_ms, e);
+                let mut failures = row.failures.0.clone();
+                failures.push(JobFailure {
+                    attempt: failures.len() as i32 + 1,
+                    error: e.to_string(),
+                    instance: self.instance_id.clone(),
+                    duration_ms,
+                    failed_at: Utc::now(),
+                });
+                (JOB_FAILED, e.to_string(), failures)
+            }
+        };
+
+        // A requested run does not move the regular schedule
+        let mut next_run_at = if row.run_requested && row.next_run_at > Utc::now() {
+            row.next_run_at
+        } else {
+            next_run(&row.schedule, Utc::now()).unwrap_or_else(|e| {
+                error!("Recurring job {} has a bad schedule, retrying in an hour: {}", row.name, e);
+                Utc::now() + chrono::Duration::hours(1)
+            })
+        };
+        let failures = self.retry_or_dead_letter(&row.name, failures, &mut next_run_at).await;
+
+        match self
+            .repository
+            .finish(&row.name, &self.instance_id, status, Some(&message), duration_ms, &failures, next_run_at)
+            .await
+        {
+            Ok(true) => debug!("Recurring job {} next runs at {}", row.name, next_run_at),
+            Ok(false) => warn!(
+                "Recurring job {} outlived its {}s lease; raise scheduler.lease_secs",
+                row.name, self.config.lease_secs
+            ),
+            Err(e) => error!("Failed to record run of recurring job {}: {}", row.name, e),
+        }
+    }
+
+    /// Bring the next run forward to the job's next retry, or move the
+    /// failures to the dead-letter queue once its retry policy gives up;
+    /// returns the failures to keep on the job
+    async fn retry_or_dead_letter(
+        &self,
+        name: &str,
+        failures: Vec<JobFailure>,
+        next_run_at: &mut DateTime<Utc>,
+    ) -> Vec<JobFailure> {
+        if failures.is_empty() {
+            return failures;
+        }
+        let policy = self.config.retry_policy(name);
+        if let Some(delay) = policy.retry_delay(failures.len() as u32) {
+            let delay = chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::hours(1));
+            let retry_at = Utc::now() + delay;
+            if retry_at < *next_run_at {
+                *next_run_at = retry_at;
+            }
+            warn!(
+                "Recurring job {} failed attempt {} of {}; next try at {}",
+                name,
+                failures.len(),
+                policy.max_attempts,
+                next_run_at
+            );
+            return failures;
+        }
+
+        match self.repository.dead_letter(name, &failures).await {
+            Ok(entry) => {
+                error!(
+                    "Recurring job {} failed {} attempts; dead-lettered as {} (rcommerce jobs retry {})",
+                    name, entry.attempts, entry.id, entry.id
+                );
+                Vec::new()
+            }
+            Err(e) => {
+                // Keep the failures, so the next failure tries again
+                error!("Failed to dead-letter recurring job {}: {}", name, e);
+                failures
+            }
+        }
+    }
+
+    /// Register the jobs in the table, then poll for due jobs until the
+    /// process exits
+    pub fn start(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(e) = self.run_until(std::future::pending()).await {
+                error!("Failed to register recurring jobs: {}", e);
+            }
+        })
+    }
+
+    /// Register the jobs in the table and poll for due jobs until
+    /// `shutdown` completes, then wait up to `drain_timeout_secs` for the
+    /// running jobs to finish
+    pub async fn run_until(self: Arc<Self>, shutdown: impl Future<Output = ()>) -> Result<()> {
+        self.sync().await?;
+        info!(
+            "Job scheduler running as {} with {} jobs (at most {} at once): {}",
+            self.instance_id,
+            self.jobs.len(),
+            self.config.concurrency,
+            self.job_names().join(", ")
+        );
+
+        let mut ticker = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = ticker.tick() => {
+                    self.run_due().await;
+                }
+            }
+        }
+        self.drain(Duration::from_secs(self.config.drain_timeout_secs)).await;
+        Ok(())
+    }
+
+    /// Wait for running jobs to finish; true if they did within `timeout`
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let running = self.running();
+        if running == 0 {
+            return true;
+        }
+        info!("Waiting up to {}s for {} running jobs to finish", timeout.as_secs(), running);
+        let all = self.config.concurrency.max(1) as u32;
+        match tokio::time::timeout(timeout, self.slots.acquire_many(all)).await {
+            Ok(_) => {
+                info!("Running jobs finished");
+                true
+            }
+            Err(_) => {
+                warn!(
+                    "{} jobs still running after {}s; their leases run out and another instance retries them",
+                    self.running(),
+                    timeout.as_secs()
+                );
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_schedule() {
+        assert!(parse_schedule("*/15 * * * *").is_ok());
+        assert!(parse_schedule("0 3 * * MON-FRI").is_ok());
+        assert!(parse_schedule("30 0 3 * * *").is_ok());
+        assert!(parse_schedule("* * *").is_err());
+        assert!(parse_schedule("61 * * * *").is_err());
+        assert!(parse_schedule("").is_err());
+    }
+
+    #[test]
+    fn test_next_run() {
+        let from = Utc.with_ymd_and_hms(2026, 10, 15, 10, 7, 30).unwrap();
+        assert_eq!(
+            next_run("*/15 * * * *", from).unwrap(),
+            Utc.with_ymd_and_hms(2026, 10, 15, 10, 15, 0).unwrap()
+        );
+        assert_eq!(
+            next_run("0 3 * * *", from).unwrap(),
+            Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_interval_schedule() {
+        assert_eq!(interval_schedule(30), "* * * * *");
+        assert_eq!(interval_schedule(900), "*/15 * * * *");
+        assert_eq!(interval_schedule(420), "*/6 * * * *");
+        assert_eq!(interval_schedule(3600), "0 * * * *");
+        assert_eq!(interval_schedule(5 * 3600), "0 */4 * * *");
+        assert_eq!(interval_schedule(86400 * 2), "0 0 * * *");
+        for secs in [60, 900, 3600, 7200, 86400] {
+            assert!(parse_schedule(&interval_schedule(secs)).is_ok());
+        }
+    }
+}
@@ -12,6 +12,7 @@ pub mod order;
 pub mod notification;
 pub mod middleware;
 pub mod websocket;
+pub mod events;
 pub mod cache;
 pub mod jobs;
 pub mod performance;
@@ -19,6 +20,14 @@ pub mod import;
 pub mod shipping;
 pub mod media;
 pub mod tax;
+pub mod subscriptions;
+pub mod fx;
+pub mod secrets;
+pub mod marketplace;
+pub mod automation;
+pub mod reports;
+pub mod observability;
+pub mod analytics;
 
 // Re-export commonly used types
 pub use error::{Error, Result};
@@ -27,6 +36,7 @@ pub use models::{Currency, Pagination, SortDirection, SortParams, ProductType, S
 pub use traits::Repository;
 pub use repository::{Database, create_pool};
 pub use db::migrate::{Migrator, auto_migrate, DbStatus};
+pub use db::partitions::{PartitionManager, PartitionInfo, PARTITION_MONTHS_AHEAD};
 pub use services::{ProductService, CustomerService, OrderService, AuthService, ApiKey, JwtClaims, Service, PaginationParams, PaginationInfo, Scope, ScopeChecker, Resource, Action, scope_presets, DunningService, DunningHistory, RetryableInvoice, RetryProcessingResult};
 pub use services::dunning_service::{self, EmailService as DunningEmailService};
 pub use services::{DigitalProductService, BundleService};
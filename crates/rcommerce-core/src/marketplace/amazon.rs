@@ -0,0 +1,438 @@
+//! Amazon Selling Partner API adapter
+//!
+//! Prices and quantities are set with Listings Items API patches of the
+//! `purchasable_offer` and `fulfillment_availability` attributes. Orders
+//! come from the Orders API; only merchant-fulfilled (MFN) orders are
+//! imported, since Amazon ships FBA orders from its own stock. Requests
+//! carry a Login with Amazon access token obtained from the seller's
+//! refresh token.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{checked, CachedToken, ExternalOrder, ExternalOrderLine, ListingUpdate, MarketplaceAdapter};
+use crate::config::AmazonMarketplaceConfig;
+use crate::models::MarketplaceChannel;
+use crate::{Error, Result};
+
+/// Order statuses of paid orders still to ship (or shipped by the seller)
+const ORDER_STATUSES: &str = "Unshipped,PartiallyShipped,Shipped";
+
+/// Amazon SP-API marketplace adapter
+pub struct AmazonAdapter {
+    client: reqwest::Client,
+    config: AmazonMarketplaceConfig,
+    token: CachedToken,
+}
+
+impl AmazonAdapter {
+    pub fn new(config: AmazonMarketplaceConfig, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+            config,
+            token: CachedToken::default(),
+        }
+    }
+
+    async fn access_token(&self) -> Result<String> {
+        let request = self.client.post(&self.config.token_url).form(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", self.config.refresh_token.as_str()),
+            ("client_id", self.config.lwa_client_id.as_str()),
+            ("client_secret", self.config.lwa_client_secret.as_str()),
+        ]);
+        self.token.get("Login with Amazon", request).await
+    }
+
+    fn url(&self, segments: &[&str]) -> Result<url::Url> {
+        let mut url = url::Url::parse(&self.config.endpoint)
+            .map_err(|e| Error::config(format!("Invalid marketplaces.amazon.endpoint: {}", e)))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::config("Invalid marketplaces.amazon.endpoint"))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn get(&self, url: url::Url, query: &[(&str, &str)]) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .header("x-amz-access-token", self.access_token().await?)
+            .send()
+            .await?;
+        Ok(checked("Amazon SP-API", response).await?.json().await?)
+    }
+
+    async fn order_items(&self, order_id: &str) -> Result<Vec<AmazonOrderItem>> {
+        let url = self.url(&["orders", "v0", "orders", order_id, "orderItems"])?;
+        let mut items = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut query = Vec::new();
+            if let Some(ref token) = next_token {
+                query.push(("NextToken", token.as_str()));
+            }
+            let page: AmazonResponse<AmazonOrderItemsPage> = serde_json::from_value(self.get(url.clone(), &query).await?)?;
+            items.extend(page.payload.order_items);
+            next_token = page.payload.next_token;
+            if next_token.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MarketplaceAdapter for AmazonAdapter {
+    fn channel(&self) -> MarketplaceChannel {
+        MarketplaceChannel::Amazon
+    }
+
+    async fn push_listing(&self, update: &ListingUpdate) -> Result<()> {
+        let url = self.url(&["listings", "2021-08-01", "items", &self.config.seller_id, &update.sku])?;
+        let response = self
+            .client
+            .patch(url)
+            .query(&[("marketplaceIds", self.config.marketplace_id.as_str())])
+            .header("x-amz-access-token", self.access_token().await?)
+            .json(&listing_patch(&self.config, update))
+            .send()
+            .await?;
+
+        let response: ListingSubmission = checked("Amazon SP-API", response).await?.json().await?;
+        response.into_result(&update.sku)
+    }
+
+    async fn orders_since(&self, since: DateTime<Utc>) -> Result<Vec<ExternalOrder>> {
+        let url = self.url(&["orders", "v0", "orders"])?;
+        let since = since.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let mut orders = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("MarketplaceIds", self.config.marketplace_id.as_str()),
+                ("LastUpdatedAfter", since.as_str()),
+                ("OrderStatuses", ORDER_STATUSES),
+                ("FulfillmentChannels", "MFN"),
+            ];
+            if let Some(ref token) = next_token {
+                query.push(("NextToken", token.as_str()));
+            }
+            let page: AmazonResponse<AmazonOrdersPage> = serde_json::from_value(self.get(url.clone(), &query).await?)?;
+            for order in page.payload.orders {
+                let items = self.order_items(&order.amazon_order_id).await?;
+                orders.push(order.into_external(items)?);
+            }
+            next_token = page.payload.next_token;
+            if next_token.is_none() {
+                return Ok(orders);
+            }
+        }
+    }
+}
+
+/// Listings Items API patch setting a listing's price and/or quantity
+pub fn listing_patch(config: &AmazonMarketplaceConfig, update: &ListingUpdate) -> serde_json::Value {
+    let mut patches = Vec::new();
+    if let Some(price) = update.price {
+        patches.push(json!({
+            "op": "replace",
+            "path": "/attributes/purchasable_offer",
+            "value": [{
+                "marketplace_id": config.marketplace_id,
+                "currency": update.currency.to_string(),
+                "our_price": [{ "schedule": [{ "value_with_tax": super::json_number(price) }] }],
+            }],
+        }));
+    }
+    if let Some(quantity) = update.quantity {
+        patches.push(json!({
+            "op": "replace",
+            "path": "/attributes/fulfillment_availability",
+            "value": [{ "fulfillment_channel_code": "DEFAULT", "quantity": quantity }],
+        }));
+    }
+    json!({ "productType": config.product_type, "patches": patches })
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingSubmission {
+    status: String,
+    #[serde(default)]
+    issues: Vec<ListingIssue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingIssue {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    severity: String,
+}
+
+impl ListingSubmission {
+    fn into_result(self, sku: &str) -> Result<()> {
+        if self.status == "ACCEPTED" {
+            return Ok(());
+        }
+        let issues: Vec<String> = self
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == "ERROR")
+            .map(|issue| format!("{} {}", issue.code, issue.message))
+            .collect();
+        Err(Error::validation(format!(
+            "Amazon rejected the update of {} ({}): {}",
+            sku,
+            self.status,
+            issues.join("; ")
+        )))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AmazonResponse<T> {
+    payload: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AmazonOrdersPage {
+    #[serde(default)]
+    orders: Vec<AmazonOrder>,
+    next_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AmazonOrderItemsPage {
+    #[serde(default)]
+    order_items: Vec<AmazonOrderItem>,
+    next_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Money {
+    currency_code: Option<String>,
+    amount: Decimal,
+}
+
+fn amount(money: &Option<Money>) -> Decimal {
+    money.as_ref().map_or(Decimal::ZERO, |money| money.amount)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AmazonOrder {
+    amazon_order_id: String,
+    purchase_date: DateTime<Utc>,
+    order_total: Option<Money>,
+    buyer_info: Option<BuyerInfo>,
+    shipping_address: Option<AmazonAddress>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BuyerInfo {
+    buyer_email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AmazonAddress {
+    name: Option<String>,
+    address_line1: Option<String>,
+    address_line2: Option<String>,
+    city: Option<String>,
+    state_or_region: Option<String>,
+    postal_code: Option<String>,
+    country_code: Option<String>,
+    phone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AmazonOrderItem {
+    #[serde(rename = "SellerSKU")]
+    seller_sku: String,
+    #[serde(default)]
+    title: String,
+    quantity_ordered: i32,
+    /// Price of all units ordered
+    item_price: Option<Money>,
+    item_tax: Option<Money>,
+    shipping_price: Option<Money>,
+    shipping_tax: Option<Money>,
+}
+
+impl AmazonOrder {
+    fn into_external(self, items: Vec<AmazonOrderItem>) -> Result<ExternalOrder> {
+        let currency = self
+            .order_total
+            .as_ref()
+            .and_then(|total| total.currency_code.clone())
+            .or_else(|| {
+                items
+                    .iter()
+                    .find_map(|item| item.item_price.as_ref().and_then(|price| price.currency_code.clone()))
+            })
+            .ok_or_else(|| Error::validation(format!("Amazon order {} has no currency", self.amazon_order_id)))?;
+
+        let subtotal: Decimal = items.iter().map(|item| amount(&item.item_price)).sum();
+        let tax_total: Decimal = items
+            .iter()
+            .map(|item| amount(&item.item_tax) + amount(&item.shipping_tax))
+            .sum();
+        let shipping_total: Decimal = items.iter().map(|item| amount(&item.shipping_price)).sum();
+        let total = self
+            .order_total
+            .as_ref()
+            .map_or(subtotal + tax_total + shipping_total, |total| total.amount);
+
+        let lines = items
+            .into_iter()
+            .filter(|item| item.quantity_ordered > 0)
+            .map(|item| ExternalOrderLine {
+                unit_price: (amount(&item.item_price) / Decimal::from(item.quantity_ordered)).round_dp(2),
+                tax_amount: amount(&item.item_tax),
+                sku: item.seller_sku,
+                title: item.title,
+                quantity: item.quantity_ordered,
+            })
+            .collect();
+
+        Ok(ExternalOrder {
+            id: self.amazon_order_id,
+            placed_at: self.purchase_date,
+            email: self.buyer_info.and_then(|buyer| buyer.buyer_email).filter(|email| !email.is_empty()),
+            currency,
+            lines,
+            subtotal,
+            tax_total,
+            shipping_total,
+            total,
+            shipping_address: self.shipping_address.map(|address| {
+                json!({
+                    "name": address.name,
+                    "address1": address.address_line1,
+                    "address2": address.address_line2,
+                    "city": address.city,
+                    "state": address.state_or_region,
+                    "zip": address.postal_code,
+                    "country": address.country_code,
+                    "phone": address.phone,
+                })
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Currency;
+    use rust_decimal_macros::dec;
+
+    fn config() -> AmazonMarketplaceConfig {
+        AmazonMarketplaceConfig {
+            seller_id: "A1SELLER".to_string(),
+            marketplace_id: "ATVPDKIKX0DER".to_string(),
+            lwa_client_id: "client".to_string(),
+            lwa_client_secret: "secret".to_string(),
+            refresh_token: "Atzr|token".to_string(),
+            endpoint: "https://sellingpartnerapi-na.amazon.com".to_string(),
+            token_url: "https://api.amazon.com/auth/o2/token".to_string(),
+            product_type: "PRODUCT".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_listing_patch() {
+        let update = ListingUpdate {
+            sku: "TEE-M".to_string(),
+            price: Some(dec!(22.99)),
+            currency: Currency::USD,
+            quantity: Some(8),
+        };
+        let patch = listing_patch(&config(), &update);
+        assert_eq!(patch["productType"], "PRODUCT");
+        assert_eq!(patch["patches"][0]["path"], "/attributes/purchasable_offer");
+        assert_eq!(patch["patches"][0]["value"][0]["currency"], "USD");
+        assert_eq!(patch["patches"][0]["value"][0]["our_price"][0]["schedule"][0]["value_with_tax"], json!(22.99));
+        assert_eq!(patch["patches"][1]["value"][0]["quantity"], 8);
+
+        let stock_only = ListingUpdate { price: None, ..update };
+        let patch = listing_patch(&config(), &stock_only);
+        assert_eq!(patch["patches"].as_array().unwrap().len(), 1);
+        assert_eq!(patch["patches"][0]["path"], "/attributes/fulfillment_availability");
+    }
+
+    #[test]
+    fn test_listing_submission() {
+        let accepted: ListingSubmission = serde_json::from_value(json!({
+            "sku": "TEE-M", "status": "ACCEPTED", "submissionId": "f1", "issues": []
+        }))
+        .unwrap();
+        assert!(accepted.into_result("TEE-M").is_ok());
+
+        let invalid: ListingSubmission = serde_json::from_value(json!({
+            "status": "INVALID",
+            "issues": [
+                { "code": "90220", "message": "'our_price' is required", "severity": "ERROR" },
+                { "code": "1", "message": "just a warning", "severity": "WARNING" }
+            ]
+        }))
+        .unwrap();
+        let error = invalid.into_result("TEE-M").unwrap_err().to_string();
+        assert!(error.contains("90220"));
+        assert!(!error.contains("warning"));
+    }
+
+    #[test]
+    fn test_order_into_external() {
+        let order: AmazonOrder = serde_json::from_value(json!({
+            "AmazonOrderId": "113-1234567-1234567",
+            "PurchaseDate": "2026-10-14T09:30:00Z",
+            "OrderStatus": "Unshipped",
+            "OrderTotal": { "CurrencyCode": "USD", "Amount": "53.97" },
+            "BuyerInfo": { "BuyerEmail": "abc123@marketplace.amazon.com" },
+            "ShippingAddress": { "Name": "Jane Doe", "City": "Seattle", "CountryCode": "US" }
+        }))
+        .unwrap();
+        let items: Vec<AmazonOrderItem> = serde_json::from_value(json!([{
+            "SellerSKU": "TEE-M",
+            "Title": "T-shirt, M",
+            "QuantityOrdered": 2,
+            "ItemPrice": { "CurrencyCode": "USD", "Amount": "45.98" },
+            "ItemTax": { "CurrencyCode": "USD", "Amount": "3.00" },
+            "ShippingPrice": { "CurrencyCode": "USD", "Amount": "4.99" }
+        }]))
+        .unwrap();
+
+        let order = order.into_external(items).unwrap();
+        assert_eq!(order.id, "113-1234567-1234567");
+        assert_eq!(order.currency, "USD");
+        assert_eq!(order.email.as_deref(), Some("abc123@marketplace.amazon.com"));
+        assert_eq!(order.subtotal, dec!(45.98));
+        assert_eq!(order.tax_total, dec!(3.00));
+        assert_eq!(order.shipping_total, dec!(4.99));
+        assert_eq!(order.total, dec!(53.97));
+        assert_eq!(order.lines[0].unit_price, dec!(22.99));
+        assert_eq!(order.lines[0].quantity, 2);
+        assert_eq!(order.shipping_address.unwrap()["city"], "Seattle");
+    }
+}
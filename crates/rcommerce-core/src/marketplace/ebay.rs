@@ -0,0 +1,486 @@
+//! eBay Sell API adapter
+//!
+//! Prices and quantities are set with the Inventory API's
+//! `bulk_update_price_quantity`; a price goes to every offer of the SKU on
+//! the configured marketplace. Orders come from the Fulfillment API; only
+//! paid orders without a cancellation are imported. Requests carry a user
+//! access token obtained from the seller's refresh token.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{checked, CachedToken, ExternalOrder, ExternalOrderLine, ListingUpdate, MarketplaceAdapter};
+use crate::config::EbayMarketplaceConfig;
+use crate::models::MarketplaceChannel;
+use crate::{Error, Result};
+
+/// OAuth scopes the refresh token must grant
+const SCOPES: &str = "https://api.ebay.com/oauth/api_scope/sell.inventory https://api.ebay.com/oauth/api_scope/sell.fulfillment";
+
+/// Orders fetched per page (the Fulfillment API maximum)
+const ORDERS_PAGE_SIZE: usize = 200;
+
+/// eBay marketplace adapter
+pub struct EbayAdapter {
+    client: reqwest::Client,
+    config: EbayMarketplaceConfig,
+    token: CachedToken,
+}
+
+impl EbayAdapter {
+    pub fn new(config: EbayMarketplaceConfig, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+            config,
+            token: CachedToken::default(),
+        }
+    }
+
+    fn api(&self, path: &str) -> String {
+        format!("{}{}", self.config.api_url.trim_end_matches('/'), path)
+    }
+
+    async fn access_token(&self) -> Result<String> {
+        let request = self
+            .client
+            .post(self.api("/identity/v1/oauth2/token"))
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", self.config.refresh_token.as_str()),
+                ("scope", SCOPES),
+            ]);
+        self.token.get("eBay OAuth", request).await
+    }
+
+    /// Ids of the SKU's offers on the configured marketplace
+    async fn offer_ids(&self, sku: &str) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(self.api("/sell/inventory/v1/offer"))
+            .query(&[("sku", sku), ("marketplace_id", self.config.marketplace_id.as_str())])
+            .bearer_auth(self.access_token().await?)
+            .send()
+            .await?;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Offer {
+            offer_id: String,
+            marketplace_id: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Offers {
+            #[serde(default)]
+            offers: Vec<Offer>,
+        }
+        let offers: Offers = checked("eBay Inventory API", response).await?.json().await?;
+        Ok(offers
+            .offers
+            .into_iter()
+            .filter(|offer| offer.marketplace_id.as_deref().map_or(true, |id| id == self.config.marketplace_id))
+            .map(|offer| offer.offer_id)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl MarketplaceAdapter for EbayAdapter {
+    fn channel(&self) -> MarketplaceChannel {
+        MarketplaceChannel::Ebay
+    }
+
+    async fn push_listing(&self, update: &ListingUpdate) -> Result<()> {
+        let offer_ids = if update.price.is_some() {
+            let ids = self.offer_ids(&update.sku).await?;
+            if ids.is_empty() {
+                return Err(Error::not_found(format!(
+                    "eBay has no offer for {} on {}",
+                    update.sku, self.config.marketplace_id
+                )));
+            }
+            ids
+        } else {
+            Vec::new()
+        };
+
+        let response = self
+            .client
+            .post(self.api("/sell/inventory/v1/bulk_update_price_quantity"))
+            .bearer_auth(self.access_token().await?)
+            .json(&price_quantity_request(update, &offer_ids))
+            .send()
+            .await?;
+
+        let response: BulkPriceQuantityResponse = checked("eBay Inventory API", response).await?.json().await?;
+        response.into_result(&update.sku)
+    }
+
+    async fn orders_since(&self, since: DateTime<Utc>) -> Result<Vec<ExternalOrder>> {
+        let filter = format!("lastmodifieddate:[{}..]", since.to_rfc3339_opts(SecondsFormat::Millis, true));
+        let mut orders = Vec::new();
+        let limit = ORDERS_PAGE_SIZE.to_string();
+        let mut offset = 0;
+        loop {
+            let offset_param = offset.to_string();
+            let response = self
+                .client
+                .get(self.api("/sell/fulfillment/v1/order"))
+                .query(&[
+                    ("filter", filter.as_str()),
+                    ("limit", limit.as_str()),
+                    ("offset", offset_param.as_str()),
+                ])
+                .bearer_auth(self.access_token().await?)
+                .send()
+                .await?;
+            let page: OrderPage = checked("eBay Fulfillment API", response).await?.json().await?;
+
+            let count = page.orders.len();
+            for order in page.orders {
+                if order.is_importable() {
+                    orders.push(order.into_external()?);
+                }
+            }
+            offset += count;
+            if page.next.is_none() || count == 0 {
+                return Ok(orders);
+            }
+        }
+    }
+}
+
+/// `bulk_update_price_quantity` request for one SKU
+pub fn price_quantity_request(update: &ListingUpdate, offer_ids: &[String]) -> serde_json::Value {
+    let mut request = json!({ "sku": update.sku });
+    if let Some(quantity) = update.quantity {
+        request["shipToLocationAvailability"] = json!({ "quantity": quantity });
+    }
+    if let Some(price) = update.price {
+        request["offers"] = offer_ids
+            .iter()
+            .map(|offer_id| {
+                json!({
+                    "offerId": offer_id,
+                    "price": { "value": price.to_string(), "currency": update.currency.to_string() },
+                })
+            })
+            .collect();
+    }
+    json!({ "requests": [request] })
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkPriceQuantityResponse {
+    #[serde(default)]
+    responses: Vec<PriceQuantityResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PriceQuantityResponse {
+    status_code: u16,
+    #[serde(default)]
+    errors: Vec<EbayError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EbayError {
+    #[serde(default)]
+    message: String,
+}
+
+impl BulkPriceQuantityResponse {
+    fn into_result(self, sku: &str) -> Result<()> {
+        let errors: Vec<String> = self
+            .responses
+            .iter()
+            .filter(|response| response.status_code != 200)
+            .map(|response| {
+                let messages: Vec<&str> = response.errors.iter().map(|e| e.message.as_str()).collect();
+                format!("{} {}", response.status_code, messages.join("; "))
+            })
+            .collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(Error::validation(format!("eBay rejected the update of {}: {}", sku, errors.join(", "))))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderPage {
+    #[serde(default)]
+    orders: Vec<EbayOrder>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Amount {
+    value: Decimal,
+    currency: Option<String>,
+}
+
+fn amount(amount: &Option<Amount>) -> Decimal {
+    amount.as_ref().map_or(Decimal::ZERO, |amount| amount.value)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EbayOrder {
+    order_id: String,
+    creation_date: DateTime<Utc>,
+    order_payment_status: String,
+    cancel_status: Option<CancelStatus>,
+    pricing_summary: PricingSummary,
+    #[serde(default)]
+    line_items: Vec<EbayLineItem>,
+    #[serde(default)]
+    fulfillment_start_instructions: Vec<FulfillmentInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelStatus {
+    cancel_state: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PricingSummary {
+    price_subtotal: Option<Amount>,
+    delivery_cost: Option<Amount>,
+    total: Option<Amount>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EbayLineItem {
+    sku: Option<String>,
+    #[serde(default)]
+    title: String,
+    quantity: i32,
+    /// Cost of all units ordered
+    line_item_cost: Option<Amount>,
+    #[serde(default)]
+    taxes: Vec<EbayTax>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EbayTax {
+    amount: Option<Amount>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FulfillmentInstruction {
+    shipping_step: Option<ShippingStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShippingStep {
+    ship_to: Option<ShipTo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShipTo {
+    full_name: Option<String>,
+    email: Option<String>,
+    contact_address: Option<ContactAddress>,
+    primary_phone: Option<Phone>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContactAddress {
+    address_line1: Option<String>,
+    address_line2: Option<String>,
+    city: Option<String>,
+    state_or_province: Option<String>,
+    postal_code: Option<String>,
+    country_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Phone {
+    phone_number: Option<String>,
+}
+
+impl EbayOrder {
+    /// Paid and no cancellation requested
+    fn is_importable(&self) -> bool {
+        self.order_payment_status == "PAID"
+            && self
+                .cancel_status
+                .as_ref()
+                .map_or(true, |status| status.cancel_state == "NONE_REQUESTED")
+    }
+
+    fn into_external(self) -> Result<ExternalOrder> {
+        let currency = self
+            .pricing_summary
+            .total
+            .as_ref()
+            .and_then(|total| total.currency.clone())
+            .ok_or_else(|| Error::validation(format!("eBay order {} has no currency", self.order_id)))?;
+
+        let lines: Vec<ExternalOrderLine> = self
+            .line_items
+            .into_iter()
+            .filter(|item| item.quantity > 0)
+            .map(|item| ExternalOrderLine {
+                unit_price: (amount(&item.line_item_cost) / Decimal::from(item.quantity)).round_dp(2),
+                tax_amount: item.taxes.iter().map(|tax| amount(&tax.amount)).sum(),
+                // Listings created outside the Inventory API have no SKU
+                sku: item.sku.unwrap_or_default(),
+                title: item.title,
+                quantity: item.quantity,
+            })
+            .collect();
+
+        let subtotal = self
+            .pricing_summary
+            .price_subtotal
+            .as_ref()
+            .map_or_else(|| lines.iter().map(|line| line.unit_price * Decimal::from(line.quantity)).sum(), |a| a.value);
+        let tax_total: Decimal = lines.iter().map(|line| line.tax_amount).sum();
+        let shipping_total = amount(&self.pricing_summary.delivery_cost);
+        let total = self
+            .pricing_summary
+            .total
+            .as_ref()
+            .map_or(subtotal + tax_total + shipping_total, |total| total.value);
+
+        let ship_to = self
+            .fulfillment_start_instructions
+            .into_iter()
+            .find_map(|instruction| instruction.shipping_step.and_then(|step| step.ship_to));
+        let email = ship_to.as_ref().and_then(|to| to.email.clone()).filter(|email| !email.is_empty());
+        let shipping_address = ship_to.map(|to| {
+            let address = to.contact_address;
+            json!({
+                "name": to.full_name,
+                "address1": address.as_ref().and_then(|a| a.address_line1.clone()),
+                "address2": address.as_ref().and_then(|a| a.address_line2.clone()),
+                "city": address.as_ref().and_then(|a| a.city.clone()),
+                "state": address.as_ref().and_then(|a| a.state_or_province.clone()),
+                "zip": address.as_ref().and_then(|a| a.postal_code.clone()),
+                "country": address.as_ref().and_then(|a| a.country_code.clone()),
+                "phone": to.primary_phone.and_then(|phone| phone.phone_number),
+            })
+        });
+
+        Ok(ExternalOrder {
+            id: self.order_id,
+            placed_at: self.creation_date,
+            email,
+            currency,
+            lines,
+            subtotal,
+            tax_total,
+            shipping_total,
+            total,
+            shipping_address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Currency;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_price_quantity_request() {
+        let update = ListingUpdate {
+            sku: "TEE-M".to_string(),
+            price: Some(dec!(21.50)),
+            currency: Currency::USD,
+            quantity: Some(4),
+        };
+        let request = price_quantity_request(&update, &["5005".to_string()]);
+        let request = &request["requests"][0];
+        assert_eq!(request["sku"], "TEE-M");
+        assert_eq!(request["shipToLocationAvailability"]["quantity"], 4);
+        assert_eq!(request["offers"][0]["offerId"], "5005");
+        assert_eq!(request["offers"][0]["price"]["value"], "21.50");
+
+        let stock_only = ListingUpdate { price: None, ..update };
+        let request = price_quantity_request(&stock_only, &[]);
+        assert!(request["requests"][0].get("offers").is_none());
+    }
+
+    #[test]
+    fn test_bulk_response() {
+        let ok: BulkPriceQuantityResponse =
+            serde_json::from_value(json!({ "responses": [{ "sku": "TEE-M", "statusCode": 200 }] })).unwrap();
+        assert!(ok.into_result("TEE-M").is_ok());
+
+        let failed: BulkPriceQuantityResponse = serde_json::from_value(json!({
+            "responses": [{ "sku": "TEE-M", "statusCode": 400, "errors": [{ "errorId": 25001, "message": "Invalid price" }] }]
+        }))
+        .unwrap();
+        assert!(failed.into_result("TEE-M").unwrap_err().to_string().contains("Invalid price"));
+    }
+
+    fn order(payment: &str, cancel: &str) -> EbayOrder {
+        serde_json::from_value(json!({
+            "orderId": "12-34567-89012",
+            "creationDate": "2026-10-14T09:30:00.000Z",
+            "orderPaymentStatus": payment,
+            "cancelStatus": { "cancelState": cancel },
+            "pricingSummary": {
+                "priceSubtotal": { "value": "40.00", "currency": "USD" },
+                "deliveryCost": { "value": "5.00", "currency": "USD" },
+                "total": { "value": "48.20", "currency": "USD" }
+            },
+            "lineItems": [{
+                "sku": "MUG-1",
+                "title": "Mug",
+                "quantity": 2,
+                "lineItemCost": { "value": "40.00", "currency": "USD" },
+                "taxes": [{ "amount": { "value": "3.20", "currency": "USD" } }]
+            }],
+            "fulfillmentStartInstructions": [{
+                "shippingStep": {
+                    "shipTo": {
+                        "fullName": "Jane Doe",
+                        "email": "jane.abc@members.ebay.com",
+                        "contactAddress": { "addressLine1": "1 Main St", "city": "Austin", "countryCode": "US" }
+                    }
+                }
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_order_into_external() {
+        assert!(order("PAID", "NONE_REQUESTED").is_importable());
+        assert!(!order("PENDING", "NONE_REQUESTED").is_importable());
+        assert!(!order("PAID", "CANCEL_REQUESTED").is_importable());
+
+        let order = order("PAID", "NONE_REQUESTED").into_external().unwrap();
+        assert_eq!(order.id, "12-34567-89012");
+        assert_eq!(order.email.as_deref(), Some("jane.abc@members.ebay.com"));
+        assert_eq!(order.subtotal, dec!(40.00));
+        assert_eq!(order.tax_total, dec!(3.20));
+        assert_eq!(order.shipping_total, dec!(5.00));
+        assert_eq!(order.total, dec!(48.20));
+        assert_eq!(order.lines[0].unit_price, dec!(20.00));
+        assert_eq!(order.shipping_address.unwrap()["city"], "Austin");
+    }
+}
@@ -0,0 +1,137 @@
+//! External marketplaces
+//!
+//! Products and variants are listed on marketplaces under marketplace SKUs
+//! (`marketplace_listings`). Whenever the price or stock a listing mirrors
+//! changes, a trigger queues the listing; the `marketplace_sync` recurring
+//! job pushes queued listings through the marketplace's adapter and
+//! imports the marketplace's new orders as store orders on its channel.
+//! Adapters are configured under `[marketplaces]`:
+//!
+//! - `amazon` - Selling Partner API (Listings Items and Orders)
+//! - `ebay` - Sell Inventory and Fulfillment APIs
+
+pub mod amazon;
+pub mod ebay;
+pub mod sync;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config::MarketplaceConfig;
+use crate::models::{Currency, MarketplaceChannel};
+use crate::{Error, Result};
+
+pub use amazon::AmazonAdapter;
+pub use ebay::EbayAdapter;
+pub use sync::{MarketplaceSync, MarketplaceSyncJob, SyncReport};
+
+/// Price and/or stock to set on a listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListingUpdate {
+    pub sku: String,
+    pub price: Option<Decimal>,
+    pub currency: Currency,
+    pub quantity: Option<i32>,
+}
+
+/// An order placed on a marketplace, ready to fulfil
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalOrder {
+    pub id: String,
+    pub placed_at: DateTime<Utc>,
+    pub email: Option<String>,
+    pub currency: String,
+    pub lines: Vec<ExternalOrderLine>,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub shipping_total: Decimal,
+    pub total: Decimal,
+    /// Address in the store's order address shape, when the marketplace shares it
+    pub shipping_address: Option<serde_json::Value>,
+}
+
+/// A line of a marketplace order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalOrderLine {
+    pub sku: String,
+    pub title: String,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub tax_amount: Decimal,
+}
+
+/// Pushes listings to and reads orders from one marketplace
+#[async_trait]
+pub trait MarketplaceAdapter: Send + Sync {
+    fn channel(&self) -> MarketplaceChannel;
+
+    /// Set the price and/or quantity of a listing
+    async fn push_listing(&self, update: &ListingUpdate) -> Result<()>;
+
+    /// Paid, uncancelled orders the store fulfils that changed after `since`
+    async fn orders_since(&self, since: DateTime<Utc>) -> Result<Vec<ExternalOrder>>;
+}
+
+/// Adapters of the configured marketplaces
+pub fn adapters_from_config(config: &MarketplaceConfig) -> Vec<Arc<dyn MarketplaceAdapter>> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let mut adapters: Vec<Arc<dyn MarketplaceAdapter>> = Vec::new();
+    if let Some(ref amazon) = config.amazon {
+        adapters.push(Arc::new(AmazonAdapter::new(amazon.clone(), timeout)));
+    }
+    if let Some(ref ebay) = config.ebay {
+        adapters.push(Arc::new(EbayAdapter::new(ebay.clone(), timeout)));
+    }
+    adapters
+}
+
+/// OAuth access token, refreshed shortly before it expires
+#[derive(Default)]
+struct CachedToken {
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl CachedToken {
+    /// The cached token, or a new one from `request` (a refresh token grant)
+    async fn get(&self, name: &str, request: reqwest::RequestBuilder) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some((ref access_token, expires)) = *token {
+            if Instant::now() < expires {
+                return Ok(access_token.clone());
+            }
+        }
+
+        let response: TokenResponse = checked(name, request.send().await?).await?.json().await?;
+        // Refresh a minute early so a token doesn't expire mid-request
+        let lifetime = Duration::from_secs(response.expires_in.saturating_sub(60));
+        *token = Some((response.access_token.clone(), Instant::now() + lifetime));
+        Ok(response.access_token)
+    }
+}
+
+/// An amount as a JSON number, as marketplace APIs expect
+fn json_number(amount: Decimal) -> serde_json::Value {
+    serde_json::from_str(&amount.normalize().to_string()).unwrap_or(serde_json::Value::Null)
+}
+
+/// The response if successful, otherwise an error with its body
+async fn checked(name: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::network(format!("{} request failed ({}): {}", name, status, body)))
+}
@@ -0,0 +1,405 @@
+//! Marketplace sync runs
+//!
+//! A run handles each configured marketplace in turn: it pushes the price
+//! and stock of listings queued since their last push (a failed push is
+//! recorded on the listing and retried next run), then imports orders
+//! changed since the last import. Order lines are matched to listings by
+//! marketplace SKU; an order with SKUs that match no listing is imported
+//! without those lines and put on hold. Imports are idempotent per
+//! marketplace order id, so the import window overlaps the previous one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::{ExternalOrder, ListingUpdate, MarketplaceAdapter};
+use crate::config::MarketplaceConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::models::{
+    Currency, MarketplaceChannel, MarketplaceListing, MarketplaceOrderImport, MarketplaceOrderImportLine,
+    PendingListing,
+};
+use crate::repository::MarketplaceRepository;
+use crate::{Error, Result};
+
+/// How far each import reaches back before the end of the previous one
+const IMPORT_OVERLAP_MINUTES: i64 = 10;
+
+/// Outcome of a sync run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub pushed: usize,
+    pub push_failures: usize,
+    pub imported: usize,
+    /// Imported orders put on hold for unmapped SKUs
+    pub on_hold: usize,
+    pub import_failures: usize,
+    /// Errors that stopped a marketplace's import
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pushed {} listings", self.pushed)?;
+        if self.push_failures > 0 {
+            write!(f, " ({} failed)", self.push_failures)?;
+        }
+        write!(f, ", imported {} orders", self.imported)?;
+        if self.on_hold > 0 {
+            write!(f, " ({} on hold for unmapped SKUs)", self.on_hold)?;
+        }
+        if self.import_failures > 0 {
+            write!(f, ", {} orders failed to import", self.import_failures)?;
+        }
+        for error in &self.errors {
+            write!(f, "; {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// What to push for a listing; None if it syncs neither price nor stock
+pub fn listing_update(pending: &PendingListing) -> Option<ListingUpdate> {
+    let listing = &pending.listing;
+    if !listing.sync_price && !listing.sync_inventory {
+        return None;
+    }
+    Some(ListingUpdate {
+        sku: listing.marketplace_sku.clone(),
+        price: listing.sync_price.then(|| listing.marketplace_price(pending.price)),
+        currency: pending.currency,
+        quantity: listing.sync_inventory.then(|| listing.marketplace_quantity(pending.inventory_quantity)),
+    })
+}
+
+/// Match a marketplace order's lines to listings by SKU
+pub fn build_import(
+    channel: MarketplaceChannel,
+    order: &ExternalOrder,
+    listings: &HashMap<String, MarketplaceListing>,
+) -> Result<MarketplaceOrderImport> {
+    let currency: Currency = order
+        .currency
+        .parse()
+        .map_err(|_| Error::validation(format!("{} order {} is in unsupported currency {}", channel, order.id, order.currency)))?;
+
+    let mut lines = Vec::new();
+    let mut unmapped_skus = Vec::new();
+    let mut unmapped_lines = Vec::new();
+    for line in &order.lines {
+        match listings.get(&line.sku) {
+            Some(listing) => lines.push(MarketplaceOrderImportLine {
+                product_id: listing.product_id,
+                variant_id: listing.variant_id,
+                sku: line.sku.clone(),
+                title: line.title.clone(),
+                quantity: line.quantity,
+                price: line.unit_price,
+                tax_amount: line.tax_amount,
+            }),
+            None => {
+                let sku = if line.sku.is_empty() { "(no SKU)" } else { line.sku.as_str() };
+                unmapped_skus.push(sku.to_string());
+                unmapped_lines.push(format!("{} x {} ({}) at {}", line.quantity, sku, line.title, line.unit_price));
+            }
+        }
+    }
+
+    let notes = (!unmapped_lines.is_empty())
+        .then(|| format!("Marketplace lines not matched to a listing: {}", unmapped_lines.join(", ")));
+
+    Ok(MarketplaceOrderImport {
+        channel,
+        marketplace_order_id: order.id.clone(),
+        order_number: format!("{}-{}", channel.order_prefix(), order.id),
+        placed_at: order.placed_at,
+        // Orders need an email; marketplaces don't always share the buyer's
+        email: order
+            .email
+            .clone()
+            .unwrap_or_else(|| format!("{}@{}.marketplace.invalid", order.id, channel)),
+        currency,
+        subtotal: order.subtotal,
+        tax_total: order.tax_total,
+        shipping_total: order.shipping_total,
+        total: order.total,
+        shipping_address: order.shipping_address.clone(),
+        notes,
+        lines,
+        unmapped_skus,
+    })
+}
+
+/// Syncs listings and orders with the configured marketplaces
+pub struct MarketplaceSync<R: MarketplaceRepository> {
+    repository: R,
+    adapters: Vec<Arc<dyn MarketplaceAdapter>>,
+    config: MarketplaceConfig,
+}
+
+impl<R: MarketplaceRepository> MarketplaceSync<R> {
+    pub fn new(repository: R, adapters: Vec<Arc<dyn MarketplaceAdapter>>, config: MarketplaceConfig) -> Self {
+        Self { repository, adapters, config }
+    }
+
+    /// Channels with an adapter
+    pub fn channels(&self) -> Vec<MarketplaceChannel> {
+        self.adapters.iter().map(|adapter| adapter.channel()).collect()
+    }
+
+    /// Push up to `batch_size` queued listings of the adapter's marketplace
+    pub async fn push_listings(&self, adapter: &dyn MarketplaceAdapter, report: &mut SyncReport) -> Result<()> {
+        let channel = adapter.channel();
+        let pending = self
+            .repository
+            .pending_listings(channel, i64::from(self.config.batch_size))
+            .await?;
+
+        for listing in pending {
+            let id = listing.listing.id;
+            let Some(update) = listing_update(&listing) else {
+                self.repository.mark_synced(id, listing.listing.changed_at, None, None).await?;
+                continue;
+            };
+
+            match adapter.push_listing(&update).await {
+                Ok(()) => {
+                    self.repository
+                        .mark_synced(id, listing.listing.changed_at, update.price, update.quantity)
+                        .await?;
+                    report.pushed += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to push {} listing {}: {}", channel, update.sku, e);
+                    self.repository.mark_failed(id, &e.to_string()).await?;
+                    report.push_failures += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Import orders changed since the last import of the adapter's marketplace
+    pub async fn import_orders(&self, adapter: &dyn MarketplaceAdapter, report: &mut SyncReport) -> Result<()> {
+        let channel = adapter.channel();
+        let started = Utc::now();
+        let since = match self.repository.orders_synced_until(channel).await? {
+            Some(until) => until - Duration::minutes(IMPORT_OVERLAP_MINUTES),
+            None => started - Duration::hours(i64::from(self.config.order_lookback_hours)),
+        };
+
+        let orders = adapter.orders_since(since).await?;
+        let skus: Vec<String> = orders
+            .iter()
+            .flat_map(|order| order.lines.iter().map(|line| line.sku.clone()))
+            .filter(|sku| !sku.is_empty())
+            .collect();
+        let listings: HashMap<String, MarketplaceListing> = if skus.is_empty() {
+            HashMap::new()
+        } else {
+            self.repository
+                .listings_by_sku(channel, &skus)
+                .await?
+                .into_iter()
+                .map(|listing| (listing.marketplace_sku.clone(), listing))
+                .collect()
+        };
+
+        let mut failures = 0;
+        for order in &orders {
+            let imported = match build_import(channel, order, &listings) {
+                Ok(import) => self.repository.import_order(&import).await,
+                Err(e) => Err(e),
+            };
+            match imported {
+                Ok(Some(record)) => {
+                    report.imported += 1;
+                    if record.unmapped_skus.is_empty() {
+                        info!("Imported {} order {} as order {}", channel, order.id, record.order_id);
+                    } else {
+                        report.on_hold += 1;
+                        warn!(
+                            "Imported {} order {} on hold: no listing for {}",
+                            channel,
+                            order.id,
+                            record.unmapped_skus.join(", ")
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Failed to import {} order {}: {}", channel, order.id, e);
+                    failures += 1;
+                }
+            }
+        }
+        report.import_failures += failures;
+
+        // Failed orders are fetched again next run
+        if failures == 0 {
+            self.repository.set_orders_synced_until(channel, started).await?;
+        }
+        Ok(())
+    }
+
+    /// Push listings and import orders for every marketplace
+    pub async fn run(&self) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        for adapter in &self.adapters {
+            let channel = adapter.channel();
+            if let Err(e) = self.push_listings(adapter.as_ref(), &mut report).await {
+                report.errors.push(format!("{} listings: {}", channel, e));
+            }
+            if let Err(e) = self.import_orders(adapter.as_ref(), &mut report).await {
+                report.errors.push(format!("{} orders: {}", channel, e));
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// The `marketplace_sync` recurring job
+pub struct MarketplaceSyncJob<R: MarketplaceRepository> {
+    sync: MarketplaceSync<R>,
+}
+
+impl<R: MarketplaceRepository> MarketplaceSyncJob<R> {
+    pub fn new(sync: MarketplaceSync<R>) -> Self {
+        Self { sync }
+    }
+}
+
+#[async_trait]
+impl<R: MarketplaceRepository> RecurringJob for MarketplaceSyncJob<R> {
+    fn name(&self) -> &str {
+        "marketplace_sync"
+    }
+
+    fn description(&self) -> &str {
+        "Push marketplace listing prices and stock, import marketplace orders"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.sync.config.sync_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        let report = self.sync.run().await?;
+        // Fail the run so `rcommerce jobs list` shows what went wrong
+        if !report.errors.is_empty() || report.import_failures > 0 {
+            return Err(Error::Other(report.to_string()));
+        }
+        Ok(report.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marketplace::ExternalOrderLine;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    fn listing(sku: &str) -> MarketplaceListing {
+        let now = Utc::now();
+        MarketplaceListing {
+            id: Uuid::new_v4(),
+            channel: MarketplaceChannel::Ebay,
+            marketplace_sku: sku.to_string(),
+            product_id: Uuid::new_v4(),
+            variant_id: Some(Uuid::new_v4()),
+            price_adjustment_percent: dec!(10),
+            stock_buffer: 1,
+            sync_price: true,
+            sync_inventory: true,
+            active: true,
+            changed_at: now,
+            synced_at: None,
+            last_synced_price: None,
+            last_synced_quantity: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn order(currency: &str) -> ExternalOrder {
+        let line = |sku: &str| ExternalOrderLine {
+            sku: sku.to_string(),
+            title: format!("Item {}", sku),
+            quantity: 1,
+            unit_price: dec!(10.00),
+            tax_amount: Decimal::ZERO,
+        };
+        ExternalOrder {
+            id: "12-34567-89012".to_string(),
+            placed_at: Utc::now(),
+            email: None,
+            currency: currency.to_string(),
+            lines: vec![line("MUG-1"), line("MUG-2")],
+            subtotal: dec!(20.00),
+            tax_total: Decimal::ZERO,
+            shipping_total: Decimal::ZERO,
+            total: dec!(20.00),
+            shipping_address: None,
+        }
+    }
+
+    #[test]
+    fn test_listing_update() {
+        let mut pending = PendingListing {
+            listing: listing("MUG-1"),
+            price: dec!(20.00),
+            currency: Currency::EUR,
+            inventory_quantity: 5,
+        };
+        let update = listing_update(&pending).unwrap();
+        assert_eq!(update.price, Some(dec!(22.00)));
+        assert_eq!(update.quantity, Some(4));
+        assert_eq!(update.currency, Currency::EUR);
+
+        pending.listing.sync_price = false;
+        assert_eq!(listing_update(&pending).unwrap().price, None);
+        pending.listing.sync_inventory = false;
+        assert!(listing_update(&pending).is_none());
+    }
+
+    #[test]
+    fn test_build_import() {
+        let mapped = listing("MUG-1");
+        let listings = HashMap::from([("MUG-1".to_string(), mapped.clone())]);
+
+        let import = build_import(MarketplaceChannel::Ebay, &order("USD"), &listings).unwrap();
+        assert_eq!(import.order_number, "EBAY-12-34567-89012");
+        assert_eq!(import.email, "12-34567-89012@ebay.marketplace.invalid");
+        assert_eq!(import.lines.len(), 1);
+        assert_eq!(import.lines[0].variant_id, mapped.variant_id);
+        assert_eq!(import.unmapped_skus, vec!["MUG-2".to_string()]);
+        assert!(import.notes.unwrap().contains("MUG-2"));
+        // Totals are the marketplace's, unmapped lines included
+        assert_eq!(import.total, dec!(20.00));
+
+        assert!(build_import(MarketplaceChannel::Ebay, &order("XYZ"), &listings).is_err());
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = SyncReport {
+            pushed: 3,
+            push_failures: 1,
+            imported: 2,
+            on_hold: 1,
+            ..SyncReport::default()
+        };
+        assert_eq!(
+            report.to_string(),
+            "pushed 3 listings (1 failed), imported 2 orders (1 on hold for unmapped SKUs)"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Image formats and resizing
+//!
+//! Uploaded images are identified by their magic bytes, measured from
+//! their headers, and resized into the configured sizes by an
+//! [`ImageProcessor`] — by default ImageMagick, run as a subprocess.
+
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::ImageSize;
+use crate::{Error, Result};
+
+/// Image formats accepted for product images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+}
+
+impl ImageFormat {
+    /// The format of an image from its first bytes
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    /// The format of a stored file from its extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// Width and height of an image, read from its header
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    match ImageFormat::detect(data)? {
+        ImageFormat::Png => {
+            // IHDR is always the first chunk
+            if data.len() < 24 || &data[12..16] != b"IHDR" {
+                return None;
+            }
+            Some((be_u32(&data[16..20]), be_u32(&data[20..24])))
+        }
+        ImageFormat::Gif => {
+            let width = u16::from_le_bytes([*data.get(6)?, *data.get(7)?]);
+            let height = u16::from_le_bytes([*data.get(8)?, *data.get(9)?]);
+            Some((width as u32, height as u32))
+        }
+        ImageFormat::Webp => webp_dimensions(data),
+        ImageFormat::Jpeg => jpeg_dimensions(data),
+    }
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let chunk = data.get(12..16)?;
+    let body = data.get(20..)?;
+    match chunk {
+        b"VP8 " => {
+            // Frame tag (3 bytes), start code (3 bytes), then 14-bit sizes
+            let bytes = body.get(6..10)?;
+            let width = u16::from_le_bytes([bytes[0], bytes[1]]) & 0x3FFF;
+            let height = u16::from_le_bytes([bytes[2], bytes[3]]) & 0x3FFF;
+            Some((width as u32, height as u32))
+        }
+        b"VP8L" => {
+            // Signature byte, then 14-bit width - 1 and height - 1
+            let bytes = body.get(1..5)?;
+            let bits = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => {
+            // Flags (4 bytes), then 24-bit canvas width - 1 and height - 1
+            let bytes = body.get(4..10)?;
+            let width = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) + 1;
+            let height = u32::from_le_bytes([bytes[3], bytes[4], bytes[5], 0]) + 1;
+            Some((width, height))
+        }
+        _ => None,
+    }
+}
+
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        // Fill bytes and markers without a length
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            i += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        // Start of frame markers, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let frame = data.get(i + 5..i + 9)?;
+            let height = u16::from_be_bytes([frame[0], frame[1]]);
+            let width = u16::from_be_bytes([frame[2], frame[3]]);
+            return Some((width as u32, height as u32));
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+/// Resizes images into the configured sizes
+#[async_trait]
+pub trait ImageProcessor: Send + Sync {
+    /// A copy of `data` resized to `size`, in the same format
+    async fn resize(&self, data: &[u8], format: ImageFormat, size: &ImageSize, quality: u8) -> Result<Vec<u8>>;
+}
+
+/// Resizes images with ImageMagick (`media.image_processing.command`)
+pub struct ImageMagickProcessor {
+    command: String,
+}
+
+impl ImageMagickProcessor {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into() }
+    }
+}
+
+/// ImageMagick arguments reading the image from stdin and writing the
+/// resized image to stdout. Images are never enlarged; cropped sizes are
+/// filled and cut to exactly the size, centered.
+fn resize_args(format: ImageFormat, size: &ImageSize, quality: u8) -> Vec<String> {
+    let ext = format.extension();
+    // Only the first frame of animated GIFs
+    let input = match format {
+        ImageFormat::Gif => format!("{}:-[0]", ext),
+        _ => format!("{}:-", ext),
+    };
+    let geometry = format!("{}x{}", size.width, size.height);
+
+    let mut args = vec![input, "-auto-orient".to_string(), "-strip".to_string(), "-thumbnail".to_string()];
+    if size.crop {
+        args.extend([
+            format!("{}^", geometry),
+            "-gravity".to_string(),
+            "center".to_string(),
+            "-extent".to_string(),
+            geometry,
+        ]);
+    } else {
+        args.push(format!("{}>", geometry));
+    }
+    args.extend(["-quality".to_string(), quality.to_string(), format!("{}:-", ext)]);
+    args
+}
+
+#[async_trait]
+impl ImageProcessor for ImageMagickProcessor {
+    async fn resize(&self, data: &[u8], format: ImageFormat, size: &ImageSize, quality: u8) -> Result<Vec<u8>> {
+        let mut child = tokio::process::Command::new(&self.command)
+            .args(resize_args(format, size, quality))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Error::storage(format!("Failed to run {}: {}", self.command, e)))?;
+
+        // Write stdin while reading stdout so large images cannot deadlock
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = data.to_vec();
+        let writer = tokio::spawn(async move {
+            let result = stdin.write_all(&input).await;
+            drop(stdin);
+            result
+        });
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| Error::storage(format!("Failed to run {}: {}", self.command, e)))?;
+        let _ = writer.await;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(Error::storage(format!(
+                "Failed to resize image to {}: {}",
+                size.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(crop: bool) -> ImageSize {
+        ImageSize {
+            name: "thumbnail".to_string(),
+            width: 150,
+            height: 100,
+            crop,
+        }
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(ImageFormat::detect(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::detect(b"GIF89a\x01\x00"), Some(ImageFormat::Gif));
+        assert_eq!(ImageFormat::detect(b"RIFF\0\0\0\0WEBPVP8 "), Some(ImageFormat::Webp));
+        assert_eq!(ImageFormat::detect(b"<svg></svg>"), None);
+        assert_eq!(ImageFormat::from_extension("JPEG"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_extension("svg"), None);
+    }
+
+    #[test]
+    fn test_png_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(640u32.to_be_bytes());
+        png.extend(480u32.to_be_bytes());
+        assert_eq!(image_dimensions(&png), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_gif_dimensions() {
+        assert_eq!(image_dimensions(b"GIF89a\x20\x03\x58\x02"), Some((800, 600)));
+    }
+
+    #[test]
+    fn test_jpeg_dimensions() {
+        let jpeg = [
+            0xFF, 0xD8, // SOI
+            0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, // APP0 with a 2 byte body
+            0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80, // SOF0: 480 high, 640 wide
+        ];
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_webp_dimensions() {
+        let mut vp8x = b"RIFF\0\0\0\0WEBPVP8X\0\0\0\0\0\0\0\0".to_vec();
+        vp8x.extend([0x7F, 0x02, 0x00, 0xDF, 0x01, 0x00]);
+        assert_eq!(image_dimensions(&vp8x), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_resize_args() {
+        assert_eq!(
+            resize_args(ImageFormat::Png, &size(false), 85).join(" "),
+            "png:- -auto-orient -strip -thumbnail 150x100> -quality 85 png:-"
+        );
+        assert_eq!(
+            resize_args(ImageFormat::Gif, &size(true), 85).join(" "),
+            "gif:-[0] -auto-orient -strip -thumbnail 150x100^ -gravity center -extent 150x100 -quality 85 gif:-"
+        );
+    }
+}
@@ -1,8 +1,12 @@
 //! Media module for file handling and storage
 //!
 //! Provides file upload, storage, and retrieval services for digital products
-//! and other media assets.
+//! and other media assets, and storage and resizing for product images.
 
 pub mod file_upload;
+pub mod image;
+pub mod storage;
 
 pub use file_upload::{FileUploadService, FileMetadata, StorageBackend, S3Config};
+pub use image::{image_dimensions, ImageFormat, ImageMagickProcessor, ImageProcessor};
+pub use storage::{media_storage, LocalStorage, MediaStorage, S3Storage};
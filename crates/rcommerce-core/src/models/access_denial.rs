@@ -0,0 +1,55 @@
+//! Access denial audit log
+//!
+//! Requests refused by the admin and API key access controls are recorded
+//! so operators can see who was turned away and why.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Why a request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "access_denial_reason", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AccessDenialReason {
+    /// Admin request without a verified TLS client certificate
+    ClientCertificateRequired,
+    /// API key used from an address outside its allowlist
+    IpNotAllowed,
+}
+
+impl AccessDenialReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessDenialReason::ClientCertificateRequired => "client_certificate_required",
+            AccessDenialReason::IpNotAllowed => "ip_not_allowed",
+        }
+    }
+}
+
+/// A recorded denial
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AccessDenial {
+    pub id: Uuid,
+    pub reason: AccessDenialReason,
+    pub method: String,
+    pub path: String,
+    pub client_ip: Option<String>,
+    pub api_key_id: Option<Uuid>,
+    pub customer_id: Option<Uuid>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A denial to record
+#[derive(Debug, Clone)]
+pub struct NewAccessDenial {
+    pub reason: AccessDenialReason,
+    pub method: String,
+    pub path: String,
+    pub client_ip: Option<String>,
+    pub api_key_id: Option<Uuid>,
+    pub customer_id: Option<Uuid>,
+    pub detail: Option<String>,
+}
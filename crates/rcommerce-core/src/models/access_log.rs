@@ -0,0 +1,86 @@
+//! Access log
+//!
+//! One entry per API request, for debugging partner integrations. Entries
+//! hold no query strings, bodies or full client addresses: IPs are cut to
+//! their network before they are stored.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// A logged request
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AccessLogEntry {
+    pub id: i64,
+    pub method: String,
+    pub path: String,
+    pub status: i16,
+    pub latency_ms: i32,
+    /// Public prefix of the API key used, if any
+    pub api_key_prefix: Option<String>,
+    /// Client network, e.g. `203.0.113.0` or `2001:db8:abcd::`
+    pub client_network: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A request to log
+#[derive(Debug, Clone)]
+pub struct NewAccessLogEntry {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub latency_ms: u32,
+    pub api_key_prefix: Option<String>,
+    pub client_network: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Access log search; all conditions must match
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccessLogFilter {
+    pub api_key_prefix: Option<String>,
+    /// Paths starting with this
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub status: Option<i16>,
+    /// Statuses at or above this, e.g. 400 for all errors
+    pub min_status: Option<i16>,
+    /// Requests slower than this
+    pub min_latency_ms: Option<i32>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Entries older than this id, for paging back
+    pub before_id: Option<i64>,
+}
+
+/// The network of an address: IPv4 /24, IPv6 /48
+pub fn truncate_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => truncate_ipv4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => truncate_ipv4(v4),
+            None => {
+                let segments = v6.segments();
+                Ipv6Addr::new(segments[0], segments[1], segments[2], 0, 0, 0, 0, 0).to_string()
+            }
+        },
+    }
+}
+
+fn truncate_ipv4(ip: Ipv4Addr) -> String {
+    let [a, b, c, _] = ip.octets();
+    Ipv4Addr::new(a, b, c, 0).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_ip() {
+        assert_eq!(truncate_ip("203.0.113.77".parse().unwrap()), "203.0.113.0");
+        assert_eq!(truncate_ip("2001:db8:abcd:12:1::7".parse().unwrap()), "2001:db8:abcd::");
+        assert_eq!(truncate_ip("::ffff:198.51.100.9".parse().unwrap()), "198.51.100.0");
+    }
+}
@@ -0,0 +1,239 @@
+//! Customer account models
+//!
+//! What the account portal shows customers of their own orders, saved
+//! payment methods and notification preferences. See
+//! `crate::services::AccountService`.
+
+use chrono::{DateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{Currency, FulfillmentStatus, OrderStatus, PaymentStatus};
+use crate::payment::agnostic::PaymentMethodData;
+use crate::{Error, Result};
+
+/// An order in the customer's order history
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AccountOrder {
+    pub id: Uuid,
+    pub order_number: String,
+    pub currency: Currency,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub shipping_total: Decimal,
+    pub discount_total: Decimal,
+    pub total: Decimal,
+    pub status: OrderStatus,
+    pub payment_status: PaymentStatus,
+    pub fulfillment_status: Option<FulfillmentStatus>,
+    pub item_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A line of an order
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AccountOrderLine {
+    pub id: Uuid,
+    /// None once the product was deleted
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+    pub title: String,
+    pub variant_title: Option<String>,
+    pub sku: Option<String>,
+    pub quantity: i32,
+    pub price: Decimal,
+    pub total: Decimal,
+    pub image_url: Option<String>,
+    /// The product is still sold, so the line can be reordered
+    pub available: bool,
+    /// Part of a bundle line; reordered with the bundle
+    #[serde(skip)]
+    pub is_bundle_component: bool,
+}
+
+/// A shipment of an order with its tracking
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AccountShipment {
+    pub id: Uuid,
+    pub status: FulfillmentStatus,
+    pub tracking_company: Option<String>,
+    pub tracking_number: Option<String>,
+    pub tracking_url: Option<String>,
+    /// The carrier's last status (in_transit, delivered, exception...)
+    pub tracking_status: Option<String>,
+    pub tracking_detail: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// An order with its lines and shipments
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountOrderDetail {
+    #[serde(flatten)]
+    pub order: AccountOrder,
+    pub items: Vec<AccountOrderLine>,
+    pub shipments: Vec<AccountShipment>,
+}
+
+/// A payment method the customer saved
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SavedPaymentMethod {
+    pub id: Uuid,
+    pub gateway: String,
+    /// Gateway token to pay with
+    pub token: String,
+    pub method_type: String,
+    pub card_brand: Option<String>,
+    pub last_four: Option<String>,
+    pub exp_month: Option<String>,
+    pub exp_year: Option<String>,
+    pub cardholder_name: Option<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Save a payment method; the gateway defaults to the default gateway
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddPaymentMethodRequest {
+    pub gateway_id: Option<String>,
+    pub payment_method_data: PaymentMethodData,
+    /// The customer's first payment method is always the default
+    #[serde(default)]
+    pub make_default: bool,
+}
+
+/// Which notifications a customer receives
+#[derive(Debug, Clone, PartialEq, Serialize, FromRow)]
+pub struct CustomerNotificationPreferences {
+    pub email_enabled: bool,
+    pub sms_enabled: bool,
+    pub push_enabled: bool,
+    pub marketing_emails: bool,
+    pub order_updates: bool,
+    pub shipping_updates: bool,
+    /// No notifications are wanted between these times
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+}
+
+impl Default for CustomerNotificationPreferences {
+    /// Matches the column defaults
+    fn default() -> Self {
+        Self {
+            email_enabled: true,
+            sms_enabled: false,
+            push_enabled: false,
+            marketing_emails: true,
+            order_updates: true,
+            shipping_updates: true,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+        }
+    }
+}
+
+/// Change notification preferences; omitted fields are kept. Quiet hours
+/// are set (or, with nulls, cleared) together.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateNotificationPreferences {
+    pub email_enabled: Option<bool>,
+    pub sms_enabled: Option<bool>,
+    pub push_enabled: Option<bool>,
+    pub marketing_emails: Option<bool>,
+    pub order_updates: Option<bool>,
+    pub shipping_updates: Option<bool>,
+    #[serde(default, with = "quiet_hours")]
+    pub quiet_hours: Option<Option<QuietHours>>,
+}
+
+/// Start and end of quiet hours; they may span midnight
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+mod quiet_hours {
+    use serde::{Deserialize, Deserializer};
+
+    use super::QuietHours;
+
+    /// A present null clears quiet hours, an absent field keeps them
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<QuietHours>>, D::Error> {
+        Option::<QuietHours>::deserialize(deserializer).map(Some)
+    }
+}
+
+impl UpdateNotificationPreferences {
+    /// Apply the changes to the current preferences
+    pub fn apply(&self, current: &CustomerNotificationPreferences) -> Result<CustomerNotificationPreferences> {
+        let mut preferences = current.clone();
+        let flags = [
+            (self.email_enabled, &mut preferences.email_enabled),
+            (self.sms_enabled, &mut preferences.sms_enabled),
+            (self.push_enabled, &mut preferences.push_enabled),
+            (self.marketing_emails, &mut preferences.marketing_emails),
+            (self.order_updates, &mut preferences.order_updates),
+            (self.shipping_updates, &mut preferences.shipping_updates),
+        ];
+        for (change, flag) in flags {
+            if let Some(value) = change {
+                *flag = value;
+            }
+        }
+        match self.quiet_hours {
+            Some(Some(hours)) => {
+                if hours.start == hours.end {
+                    return Err(Error::validation("Quiet hours must start and end at different times"));
+                }
+                preferences.quiet_hours_start = Some(hours.start);
+                preferences.quiet_hours_end = Some(hours.end);
+            }
+            Some(None) => {
+                preferences.quiet_hours_start = None;
+                preferences.quiet_hours_end = None;
+            }
+            None => {}
+        }
+        Ok(preferences)
+    }
+}
+
+/// What closing an account removed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountClosure {
+    pub customer_id: Uuid,
+    pub payment_methods_removed: usize,
+    pub sessions_revoked: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_notification_preferences() {
+        let current = CustomerNotificationPreferences::default();
+        let update: UpdateNotificationPreferences = serde_json::from_str(
+            r#"{"marketing_emails": false, "sms_enabled": true, "quiet_hours": {"start": "22:00:00", "end": "07:00:00"}}"#,
+        )
+        .unwrap();
+        let updated = update.apply(&current).unwrap();
+        assert!(!updated.marketing_emails);
+        assert!(updated.sms_enabled);
+        assert!(updated.order_updates);
+        assert_eq!(updated.quiet_hours_start, NaiveTime::from_hms_opt(22, 0, 0));
+
+        // Omitted quiet hours are kept, null clears them
+        let keep: UpdateNotificationPreferences = serde_json::from_str(r#"{"push_enabled": true}"#).unwrap();
+        assert_eq!(keep.apply(&updated).unwrap().quiet_hours_end, NaiveTime::from_hms_opt(7, 0, 0));
+        let clear: UpdateNotificationPreferences = serde_json::from_str(r#"{"quiet_hours": null}"#).unwrap();
+        assert_eq!(clear.apply(&updated).unwrap().quiet_hours_start, None);
+
+        let empty: UpdateNotificationPreferences =
+            serde_json::from_str(r#"{"quiet_hours": {"start": "08:00:00", "end": "08:00:00"}}"#).unwrap();
+        assert!(empty.apply(&current).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Order add-on models
+//!
+//! Add-ons are optional extras chosen at checkout (gift wrap, a gift
+//! message, an assembly service). Order-level add-ons apply once to the
+//! whole order; item-level ones to one cart line, optionally charged per
+//! unit. Their fulfillment instructions are printed on the pick list,
+//! along with the customer's delivery instructions and booked window.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::shipping::DeliveryDetails;
+
+/// What an add-on applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "addon_scope", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AddonScope {
+    /// Once for the whole order
+    Order,
+    /// One cart line
+    Item,
+}
+
+/// An add-on offered at checkout
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Addon {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub scope: AddonScope,
+    pub price: Decimal,
+    /// Item add-ons only: charge once per unit of the line
+    pub per_unit: bool,
+    /// Taxed like products in this category; None uses the default rate
+    pub tax_category_id: Option<Uuid>,
+    pub accepts_message: bool,
+    pub max_message_length: i32,
+    /// Shown to the warehouse on the pick list
+    pub fulfillment_instructions: Option<String>,
+    pub is_active: bool,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Addon {
+    /// Check a shopper's message against the add-on's rules
+    pub fn check_message(&self, message: Option<&str>) -> Result<(), String> {
+        let Some(message) = message else {
+            return Ok(());
+        };
+        if !self.accepts_message {
+            return Err(format!("{} does not take a message", self.name));
+        }
+        if message.chars().count() > self.max_message_length as usize {
+            return Err(format!(
+                "Message for {} is longer than {} characters",
+                self.name, self.max_message_length
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Request to create an add-on
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateAddonRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub description: Option<String>,
+    pub scope: AddonScope,
+    pub price: Decimal,
+    #[serde(default)]
+    pub per_unit: bool,
+    pub tax_category_id: Option<Uuid>,
+    #[serde(default)]
+    pub accepts_message: bool,
+    #[validate(range(min = 1, max = 2000))]
+    pub max_message_length: Option<i32>,
+    pub fulfillment_instructions: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+/// Request to update an add-on; its code and scope are fixed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateAddonRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<Decimal>,
+    pub per_unit: Option<bool>,
+    pub tax_category_id: Option<Uuid>,
+    pub accepts_message: Option<bool>,
+    #[validate(range(min = 1, max = 2000))]
+    pub max_message_length: Option<i32>,
+    pub fulfillment_instructions: Option<String>,
+    pub is_active: Option<bool>,
+    pub sort_order: Option<i32>,
+}
+
+/// Request to add an add-on to a cart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddCartAddonRequest {
+    pub addon_id: Uuid,
+    /// The cart line, for item add-ons
+    pub cart_item_id: Option<Uuid>,
+    pub message: Option<String>,
+}
+
+/// An add-on chosen on a cart, priced at the add-on's current price
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct CartAddon {
+    pub id: Uuid,
+    pub cart_id: Uuid,
+    pub cart_item_id: Option<Uuid>,
+    pub addon_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub scope: AddonScope,
+    pub unit_price: Decimal,
+    pub per_unit: bool,
+    pub tax_category_id: Option<Uuid>,
+    pub message: Option<String>,
+    pub fulfillment_instructions: Option<String>,
+    /// The line's quantity, product and variant, for item add-ons
+    pub item_quantity: Option<i32>,
+    pub product_id: Option<Uuid>,
+    pub variant_id: Option<Uuid>,
+}
+
+impl CartAddon {
+    /// Units charged
+    pub fn quantity(&self) -> i32 {
+        match self.item_quantity {
+            Some(quantity) if self.per_unit => quantity.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Price for all units charged
+    pub fn total(&self) -> Decimal {
+        self.unit_price * Decimal::from(self.quantity())
+    }
+}
+
+/// Total price of a cart's add-ons
+pub fn addon_total(addons: &[CartAddon]) -> Decimal {
+    addons.iter().map(CartAddon::total).sum()
+}
+
+/// An add-on as sold on an order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrderAddon {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    /// None for order-level add-ons
+    pub order_item_id: Option<Uuid>,
+    pub addon_id: Option<Uuid>,
+    pub code: String,
+    pub name: String,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub total: Decimal,
+    pub tax_amount: Decimal,
+    pub message: Option<String>,
+    pub fulfillment_instructions: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An order line to pick
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PickItem {
+    pub id: Uuid,
+    pub sku: Option<String>,
+    pub title: String,
+    pub variant_title: Option<String>,
+    pub quantity: i32,
+}
+
+/// An add-on's instructions on a pick list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickListAddon {
+    pub name: String,
+    pub quantity: i32,
+    pub message: Option<String>,
+    pub instructions: Option<String>,
+}
+
+impl From<&OrderAddon> for PickListAddon {
+    fn from(addon: &OrderAddon) -> Self {
+        Self {
+            name: addon.name.clone(),
+            quantity: addon.quantity,
+            message: addon.message.clone(),
+            instructions: addon.fulfillment_instructions.clone(),
+        }
+    }
+}
+
+/// A line on a pick list with its item add-ons
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickListLine {
+    pub order_item_id: Uuid,
+    pub sku: Option<String>,
+    pub title: String,
+    pub variant_title: Option<String>,
+    pub quantity: i32,
+    pub addons: Vec<PickListAddon>,
+}
+
+/// What to pick and pack for an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickList {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub lines: Vec<PickListLine>,
+    /// Order-level add-ons (and any whose line no longer exists)
+    pub order_addons: Vec<PickListAddon>,
+    /// Delivery instructions and booked window, for the packing slip
+    pub delivery: Option<DeliveryDetails>,
+}
+
+impl PickList {
+    pub fn build(order_id: Uuid, order_number: String, items: Vec<PickItem>, addons: &[OrderAddon]) -> Self {
+        let lines: Vec<PickListLine> = items
+            .into_iter()
+            .map(|item| PickListLine {
+                addons: addons
+                    .iter()
+                    .filter(|addon| addon.order_item_id == Some(item.id))
+                    .map(PickListAddon::from)
+                    .collect(),
+                order_item_id: item.id,
+                sku: item.sku,
+                title: item.title,
+                variant_title: item.variant_title,
+                quantity: item.quantity,
+            })
+            .collect();
+
+        let order_addons = addons
+            .iter()
+            .filter(|addon| {
+                addon
+                    .order_item_id
+                    .map_or(true, |item_id| !lines.iter().any(|line| line.order_item_id == item_id))
+            })
+            .map(PickListAddon::from)
+            .collect();
+
+        Self {
+            order_id,
+            order_number,
+            lines,
+            order_addons,
+            delivery: None,
+        }
+    }
+
+    /// Include the order's delivery preferences
+    pub fn with_delivery(mut self, delivery: DeliveryDetails) -> Self {
+        self.delivery = (!delivery.is_empty()).then_some(delivery);
+        self
+    }
+
+    /// Plain-text packing slip to print and pack with the order
+    pub fn packing_slip(&self) -> String {
+        fn addon_lines(slip: &mut String, indent: &str, addon: &PickListAddon) {
+            slip.push_str(&format!("{}+ {} x {}\n", indent, addon.quantity, addon.name));
+            if let Some(ref message) = addon.message {
+                slip.push_str(&format!("{}    Message: {}\n", indent, message));
+            }
+            if let Some(ref instructions) = addon.instructions {
+                slip.push_str(&format!("{}    {}\n", indent, instructions));
+            }
+        }
+
+        let mut slip = format!("PACKING SLIP - Order {}\n\n", self.order_number);
+        if let Some(ref delivery) = self.delivery {
+            if let Some(ref window) = delivery.window {
+                slip.push_str(&format!("Deliver on: {}\n", window.describe()));
+            }
+            if let Some(ref instructions) = delivery.instructions {
+                slip.push_str(&format!("Delivery instructions: {}\n", instructions));
+            }
+            slip.push('\n');
+        }
+        for line in &self.lines {
+            let name = match line.variant_title {
+                Some(ref variant) => format!("{} - {}", line.title, variant),
+                None => line.title.clone(),
+            };
+            let sku = line.sku.as_deref().map(|sku| format!(" [{}]", sku)).unwrap_or_default();
+            slip.push_str(&format!("{} x {}{}\n", line.quantity, name, sku));
+            for addon in &line.addons {
+                addon_lines(&mut slip, "    ", addon);
+            }
+        }
+        if !self.order_addons.is_empty() {
+            slip.push_str("\nWith this order:\n");
+            for addon in &self.order_addons {
+                addon_lines(&mut slip, "", addon);
+            }
+        }
+        slip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn cart_addon(per_unit: bool, item_quantity: Option<i32>) -> CartAddon {
+        CartAddon {
+            id: Uuid::new_v4(),
+            cart_id: Uuid::new_v4(),
+            cart_item_id: item_quantity.map(|_| Uuid::new_v4()),
+            addon_id: Uuid::new_v4(),
+            code: "gift_wrap".to_string(),
+            name: "Gift wrap".to_string(),
+            scope: if item_quantity.is_some() { AddonScope::Item } else { AddonScope::Order },
+            unit_price: dec!(4.50),
+            per_unit,
+            tax_category_id: None,
+            message: None,
+            fulfillment_instructions: None,
+            item_quantity,
+            product_id: None,
+            variant_id: None,
+        }
+    }
+
+    #[test]
+    fn test_cart_addon_pricing() {
+        assert_eq!(cart_addon(false, None).total(), dec!(4.50));
+        assert_eq!(cart_addon(false, Some(3)).total(), dec!(4.50));
+        assert_eq!(cart_addon(true, Some(3)).quantity(), 3);
+        assert_eq!(cart_addon(true, Some(3)).total(), dec!(13.50));
+        assert_eq!(addon_total(&[cart_addon(true, Some(2)), cart_addon(false, None)]), dec!(13.50));
+    }
+
+    #[test]
+    fn test_check_message() {
+        let mut addon = Addon {
+            id: Uuid::new_v4(),
+            code: "gift_message".to_string(),
+            name: "Gift message".to_string(),
+            description: None,
+            scope: AddonScope::Order,
+            price: Decimal::ZERO,
+            per_unit: false,
+            tax_category_id: None,
+            accepts_message: true,
+            max_message_length: 5,
+            fulfillment_instructions: None,
+            is_active: true,
+            sort_order: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(addon.check_message(None).is_ok());
+        assert!(addon.check_message(Some("Hi!")).is_ok());
+        assert!(addon.check_message(Some("Hello there")).is_err());
+
+        addon.accepts_message = false;
+        assert!(addon.check_message(Some("Hi!")).is_err());
+    }
+
+    #[test]
+    fn test_pick_list_groups_addons_by_line() {
+        let item_id = Uuid::new_v4();
+        let addon = |order_item_id: Option<Uuid>, name: &str| OrderAddon {
+            id: Uuid::new_v4(),
+            order_id: Uuid::nil(),
+            order_item_id,
+            addon_id: None,
+            code: name.to_lowercase(),
+            name: name.to_string(),
+            quantity: 1,
+            unit_price: dec!(5),
+            total: dec!(5),
+            tax_amount: Decimal::ZERO,
+            message: None,
+            fulfillment_instructions: Some(format!("{} instructions", name)),
+            created_at: Utc::now(),
+        };
+        let addons = vec![
+            addon(Some(item_id), "Wrap"),
+            addon(None, "Card"),
+            addon(Some(Uuid::new_v4()), "Assembly"),
+        ];
+        let items = vec![PickItem {
+            id: item_id,
+            sku: Some("MUG-1".to_string()),
+            title: "Mug".to_string(),
+            variant_title: None,
+            quantity: 2,
+        }];
+
+        let pick_list = PickList::build(Uuid::nil(), "1001".to_string(), items, &addons);
+        assert_eq!(pick_list.lines[0].addons.len(), 1);
+        assert_eq!(pick_list.lines[0].addons[0].name, "Wrap");
+        let order_level: Vec<_> = pick_list.order_addons.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(order_level, ["Card", "Assembly"]);
+    }
+
+    #[test]
+    fn test_packing_slip_shows_delivery() {
+        let items = vec![PickItem {
+            id: Uuid::new_v4(),
+            sku: Some("MUG-1".to_string()),
+            title: "Mug".to_string(),
+            variant_title: Some("Blue".to_string()),
+            quantity: 2,
+        }];
+        let delivery = DeliveryDetails {
+            instructions: Some("Leave with the concierge".to_string()),
+            window: Some(crate::shipping::DeliveryWindow {
+                date: chrono::NaiveDate::from_ymd_opt(2026, 10, 20).unwrap(),
+                start: None,
+                end: None,
+            }),
+        };
+
+        let slip = PickList::build(Uuid::nil(), "1001".to_string(), items, &[])
+            .with_delivery(delivery)
+            .packing_slip();
+        assert!(slip.contains("Order 1001"));
+        assert!(slip.contains("Deliver on: 2026-10-20"));
+        assert!(slip.contains("Delivery instructions: Leave with the concierge"));
+        assert!(slip.contains("2 x Mug - Blue [MUG-1]"));
+
+        let plain = PickList::build(Uuid::nil(), "1002".to_string(), Vec::new(), &[])
+            .with_delivery(DeliveryDetails::default());
+        assert!(plain.delivery.is_none());
+    }
+}
@@ -1,8 +1,154 @@
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::CreateAddressRequest;
 
 /// Address models - re-exported from common module
 pub use crate::common::Address;
 
+/// Columns of `addresses` as read into `CustomerAddress` (the table calls
+/// the state `province`)
+pub const CUSTOMER_ADDRESS_COLUMNS: &str = "id, customer_id, label, COALESCE(first_name, '') AS first_name, \
+    COALESCE(last_name, '') AS last_name, company, phone, address1, address2, city, province AS state, country, zip, \
+    is_default_shipping, is_default_billing, residential, validation_provider, validated_at, created_at, updated_at";
+
+/// An address in a customer's address book
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerAddress {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub label: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub company: Option<String>,
+    pub phone: Option<String>,
+    pub address1: String,
+    pub address2: Option<String>,
+    pub city: String,
+    pub state: Option<String>,
+    pub country: String,
+    pub zip: String,
+    pub is_default_shipping: bool,
+    pub is_default_billing: bool,
+    /// Residential flag reported by the validating carrier
+    pub residential: Option<bool>,
+    /// Shipping provider that validated the address, if any
+    pub validation_provider: Option<String>,
+    pub validated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CustomerAddress {
+    /// As the `Address` used by shipping, tax and notifications
+    pub fn to_address(&self) -> Address {
+        Address {
+            id: self.id,
+            customer_id: self.customer_id,
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            company: self.company.clone(),
+            phone: self.phone.clone(),
+            address1: self.address1.clone(),
+            address2: self.address2.clone(),
+            city: self.city.clone(),
+            state: self.state.clone(),
+            country: self.country.clone(),
+            zip: self.zip.clone(),
+            is_default_shipping: self.is_default_shipping,
+            is_default_billing: self.is_default_billing,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+impl CreateAddressRequest {
+    /// An unsaved `Address` for the customer, e.g. to validate with a carrier
+    pub fn to_address(&self, customer_id: Uuid) -> Address {
+        let now = Utc::now();
+        Address {
+            id: Uuid::nil(),
+            customer_id,
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            company: self.company.clone(),
+            phone: self.phone.clone(),
+            address1: self.address1.clone(),
+            address2: self.address2.clone(),
+            city: self.city.clone(),
+            state: self.state.clone(),
+            country: self.country.clone(),
+            zip: self.zip.clone(),
+            is_default_shipping: self.is_default_shipping,
+            is_default_billing: self.is_default_billing,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Request to change an address book entry; unset fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateAddressRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub label: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub first_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub last_name: Option<String>,
+    pub company: Option<String>,
+    #[validate(length(min = 1, max = 20))]
+    pub phone: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub address1: Option<String>,
+    #[validate(length(max = 255))]
+    pub address2: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub city: Option<String>,
+    #[validate(length(max = 100))]
+    pub state: Option<String>,
+    #[validate(length(min = 2, max = 2))]
+    pub country: Option<String>,
+    #[validate(length(min = 1, max = 20))]
+    pub zip: Option<String>,
+    pub is_default_shipping: Option<bool>,
+    pub is_default_billing: Option<bool>,
+}
+
+impl UpdateAddressRequest {
+    /// Whether a postal field changed, so the address needs validating again
+    pub fn changes_location(&self) -> bool {
+        self.address1.is_some()
+            || self.address2.is_some()
+            || self.city.is_some()
+            || self.state.is_some()
+            || self.country.is_some()
+            || self.zip.is_some()
+    }
+    
+    /// The full address after applying this update to `current`
+    pub fn apply(self, current: &CustomerAddress) -> CreateAddressRequest {
+        CreateAddressRequest {
+            label: self.label.or_else(|| current.label.clone()),
+            first_name: self.first_name.unwrap_or_else(|| current.first_name.clone()),
+            last_name: self.last_name.unwrap_or_else(|| current.last_name.clone()),
+            company: self.company.or_else(|| current.company.clone()),
+            phone: self.phone.or_else(|| current.phone.clone()),
+            address1: self.address1.unwrap_or_else(|| current.address1.clone()),
+            address2: self.address2.or_else(|| current.address2.clone()),
+            city: self.city.unwrap_or_else(|| current.city.clone()),
+            state: self.state.or_else(|| current.state.clone()),
+            country: self.country.unwrap_or_else(|| current.country.clone()),
+            zip: self.zip.unwrap_or_else(|| current.zip.clone()),
+            is_default_shipping: self.is_default_shipping.unwrap_or(current.is_default_shipping),
+            is_default_billing: self.is_default_billing.unwrap_or(current.is_default_billing),
+        }
+    }
+}
+
 /// Address type enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, Default)]
 #[sqlx(type_name = "address_type", rename_all = "snake_case")]
@@ -42,4 +188,43 @@ mod tests {
         assert_eq!(addr.first_name, "John");
         assert!(addr.is_default_shipping);
     }
+    
+    #[test]
+    fn test_update_address_request_apply() {
+        let current = CustomerAddress {
+            id: Uuid::new_v4(),
+            customer_id: Uuid::new_v4(),
+            label: Some("Home".to_string()),
+            first_name: "John".to_string(),
+            last_name: "Doe".to_string(),
+            company: None,
+            phone: None,
+            address1: "123 Main St".to_string(),
+            address2: None,
+            city: "Anytown".to_string(),
+            state: Some("CA".to_string()),
+            country: "US".to_string(),
+            zip: "12345".to_string(),
+            is_default_shipping: true,
+            is_default_billing: false,
+            residential: None,
+            validation_provider: None,
+            validated_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        
+        let relabel = UpdateAddressRequest { label: Some("Office".to_string()), ..Default::default() };
+        assert!(!relabel.changes_location());
+        let applied = relabel.apply(&current);
+        assert_eq!(applied.label.as_deref(), Some("Office"));
+        assert_eq!(applied.address1, "123 Main St");
+        assert!(applied.is_default_shipping);
+        
+        let moved = UpdateAddressRequest { zip: Some("54321".to_string()), ..Default::default() };
+        assert!(moved.changes_location());
+        assert_eq!(moved.apply(&current).zip, "54321");
+        
+        assert_eq!(current.to_address().state.as_deref(), Some("CA"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,728 @@
+//! Product attribute models
+//!
+//! Attributes are shop-wide and typed: select and multi_select attributes
+//! (color, size) take values from their options, number, boolean and text
+//! attributes hold their own. Products and variants are given values by
+//! attribute code; a variant's values also count for its product.
+//!
+//! Filterable attributes narrow product listings with `?attr=`, e.g.
+//! `color:red,blue;width:10..20;waterproof:true` (options by value,
+//! case-insensitive; numbers as `min..max` with either end open), and are
+//! counted as facets of the listing.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{Error, Result};
+
+/// Most attribute conditions in one listing filter
+pub const MAX_ATTRIBUTE_CONDITIONS: usize = 10;
+
+/// Characters option values can't hold, as they separate `?attr=` filters
+const FILTER_SEPARATORS: [char; 3] = [',', ';', ':'];
+
+/// What values an attribute holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "attribute_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeType {
+    /// Free text; never filterable
+    Text,
+    Number,
+    Boolean,
+    /// One of the options
+    Select,
+    /// Any of the options
+    MultiSelect,
+}
+
+impl AttributeType {
+    pub fn has_options(&self) -> bool {
+        matches!(self, AttributeType::Select | AttributeType::MultiSelect)
+    }
+}
+
+/// A typed attribute products and variants can be given values of
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct Attribute {
+    pub id: Uuid,
+    /// Lowercase key used in filters, e.g. `color`; never changes
+    pub code: String,
+    pub name: String,
+    pub attribute_type: AttributeType,
+    /// Shown after number values, e.g. `cm`
+    pub unit: Option<String>,
+    /// Whether listings can be filtered by it and count it as a facet
+    pub filterable: bool,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An allowed value of a select or multi_select attribute
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct AttributeOption {
+    pub id: Uuid,
+    pub attribute_id: Uuid,
+    pub value: String,
+    /// Hex color of a swatch, e.g. `#ff0000`
+    pub swatch: Option<String>,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An attribute with its options in position order
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AttributeWithOptions {
+    #[serde(flatten)]
+    pub attribute: Attribute,
+    pub options: Vec<AttributeOption>,
+}
+
+impl AttributeWithOptions {
+    /// The option with a value, case-insensitive
+    pub fn option(&self, value: &str) -> Option<&AttributeOption> {
+        let value = value.trim();
+        self.options.iter().find(|o| o.value.eq_ignore_ascii_case(value))
+    }
+}
+
+/// An option of a create or update request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct AttributeOptionInput {
+    #[validate(length(min = 1, max = 100))]
+    pub value: String,
+    pub swatch: Option<String>,
+}
+
+/// Request to create an attribute
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateAttributeRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub attribute_type: AttributeType,
+    #[validate(length(max = 20))]
+    pub unit: Option<String>,
+    /// Defaults to true except for text attributes
+    pub filterable: Option<bool>,
+    #[serde(default)]
+    pub position: i32,
+    /// Allowed values of select and multi_select attributes, in order
+    #[serde(default)]
+    #[validate]
+    pub options: Vec<AttributeOptionInput>,
+}
+
+/// Request to update an attribute; its code and type never change
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct UpdateAttributeRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(max = 20))]
+    pub unit: Option<String>,
+    pub filterable: Option<bool>,
+    pub position: Option<i32>,
+    /// Replaces the options; options kept (by value) keep their products,
+    /// values of removed ones are dropped
+    #[validate]
+    pub options: Option<Vec<AttributeOptionInput>>,
+}
+
+/// Request to set the attribute values of a product or variant
+///
+/// Replaces all of its values: a string for select and text attributes, an
+/// array of strings for multi_select, a number and a boolean for the others.
+/// Attributes left out (or null, or empty text) are removed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SetAttributeValuesRequest {
+    /// Values by attribute code
+    pub values: BTreeMap<String, serde_json::Value>,
+}
+
+/// A stored attribute value of a product or variant
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductAttributeValue {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    /// None for the product's own values
+    pub variant_id: Option<Uuid>,
+    pub attribute_id: Uuid,
+    pub option_id: Option<Uuid>,
+    pub text_value: Option<String>,
+    pub number_value: Option<Decimal>,
+    pub boolean_value: Option<bool>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A value to store, checked against its attribute
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAttributeValue {
+    pub attribute_id: Uuid,
+    pub option_id: Option<Uuid>,
+    pub text_value: Option<String>,
+    pub number_value: Option<Decimal>,
+    pub boolean_value: Option<bool>,
+}
+
+impl NewAttributeValue {
+    fn new(attribute_id: Uuid) -> Self {
+        Self { attribute_id, option_id: None, text_value: None, number_value: None, boolean_value: None }
+    }
+}
+
+/// The values to store for a set request; unknown codes and values of the
+/// wrong type are validation errors
+pub fn resolve_attribute_values(
+    schema: &[AttributeWithOptions],
+    request: &SetAttributeValuesRequest,
+) -> Result<Vec<NewAttributeValue>> {
+    let mut values = Vec::new();
+    for (code, value) in &request.values {
+        let attribute = schema
+            .iter()
+            .find(|a| a.attribute.code == *code)
+            .ok_or_else(|| Error::validation(format!("Unknown attribute '{}'", code)))?;
+        let id = attribute.attribute.id;
+        let invalid = || Error::validation(format!("Invalid value for attribute '{}'", code));
+        let option = |value: &serde_json::Value| {
+            let value = value.as_str().ok_or_else(invalid)?;
+            attribute
+                .option(value)
+                .map(|o| NewAttributeValue { option_id: Some(o.id), ..NewAttributeValue::new(id) })
+                .ok_or_else(|| Error::validation(format!("'{}' is not an option of attribute '{}'", value, code)))
+        };
+        match (attribute.attribute.attribute_type, value) {
+            (_, serde_json::Value::Null) => {}
+            (AttributeType::Text, serde_json::Value::String(text)) if !text.trim().is_empty() => {
+                if text.len() > 1000 {
+                    return Err(Error::validation(format!("Value of attribute '{}' is too long", code)));
+                }
+                values.push(NewAttributeValue { text_value: Some(text.trim().to_string()), ..NewAttributeValue::new(id) });
+            }
+            (AttributeType::Text, serde_json::Value::String(_)) => {}
+            (AttributeType::Number, value) => {
+                let number = match value {
+                    serde_json::Value::Number(number) => number.to_string().parse::<Decimal>().ok(),
+                    serde_json::Value::String(number) => number.trim().parse::<Decimal>().ok(),
+                    _ => None,
+                };
+                values.push(NewAttributeValue { number_value: Some(number.ok_or_else(invalid)?), ..NewAttributeValue::new(id) });
+            }
+            (AttributeType::Boolean, serde_json::Value::Bool(flag)) => {
+                values.push(NewAttributeValue { boolean_value: Some(*flag), ..NewAttributeValue::new(id) });
+            }
+            (AttributeType::Select, value) => values.push(option(value)?),
+            (AttributeType::MultiSelect, serde_json::Value::Array(items)) => {
+                let mut options: Vec<NewAttributeValue> = Vec::with_capacity(items.len());
+                for item in items {
+                    let value = option(item)?;
+                    if !options.contains(&value) {
+                        options.push(value);
+                    }
+                }
+                values.extend(options);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(values)
+}
+
+/// An attribute value of a product or variant, for display
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AssignedAttribute {
+    pub code: String,
+    pub name: String,
+    pub attribute_type: AttributeType,
+    pub unit: Option<String>,
+    /// A string, array of strings or boolean; numbers as decimal strings,
+    /// like prices
+    pub value: serde_json::Value,
+}
+
+/// The values of one product or variant by attribute, in attribute order
+pub fn assigned_attributes(schema: &[AttributeWithOptions], values: &[&ProductAttributeValue]) -> Vec<AssignedAttribute> {
+    schema
+        .iter()
+        .filter_map(|attribute| {
+            let own: Vec<_> = values.iter().filter(|v| v.attribute_id == attribute.attribute.id).collect();
+            let first = own.first()?;
+            let option_value = |v: &ProductAttributeValue| {
+                attribute
+                    .options
+                    .iter()
+                    .find(|o| Some(o.id) == v.option_id)
+                    .map(|o| serde_json::Value::String(o.value.clone()))
+            };
+            let value = match attribute.attribute.attribute_type {
+                AttributeType::Text => serde_json::json!(first.text_value),
+                AttributeType::Number => serde_json::json!(first.number_value.map(|n| n.normalize())),
+                AttributeType::Boolean => serde_json::json!(first.boolean_value),
+                AttributeType::Select => option_value(first)?,
+                AttributeType::MultiSelect => {
+                    // In option order, not the order they were set in
+                    let chosen: Vec<_> = attribute
+                        .options
+                        .iter()
+                        .filter(|o| own.iter().any(|v| v.option_id == Some(o.id)))
+                        .map(|o| serde_json::Value::String(o.value.clone()))
+                        .collect();
+                    serde_json::Value::Array(chosen)
+                }
+            };
+            Some(AssignedAttribute {
+                code: attribute.attribute.code.clone(),
+                name: attribute.attribute.name.clone(),
+                attribute_type: attribute.attribute.attribute_type,
+                unit: attribute.attribute.unit.clone(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// The attribute values of a product and of its variants
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProductAttributes {
+    pub product: Vec<AssignedAttribute>,
+    pub variants: HashMap<Uuid, Vec<AssignedAttribute>>,
+}
+
+impl ProductAttributes {
+    /// Values of a product and its variants by attribute
+    pub fn new(schema: &[AttributeWithOptions], values: &[ProductAttributeValue]) -> Self {
+        let mut by_variant: HashMap<Option<Uuid>, Vec<&ProductAttributeValue>> = HashMap::new();
+        for value in values {
+            by_variant.entry(value.variant_id).or_default().push(value);
+        }
+        let product = by_variant
+            .remove(&None)
+            .map(|values| assigned_attributes(schema, &values))
+            .unwrap_or_default();
+        let variants = by_variant
+            .into_iter()
+            .filter_map(|(variant_id, values)| Some((variant_id?, assigned_attributes(schema, &values))))
+            .collect();
+        Self { product, variants }
+    }
+
+    pub fn variant(&self, variant_id: Uuid) -> Vec<AssignedAttribute> {
+        self.variants.get(&variant_id).cloned().unwrap_or_default()
+    }
+}
+
+/// Products an attribute condition keeps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttributeMatch {
+    /// Having any of the options
+    Options(Vec<Uuid>),
+    Boolean(bool),
+    /// Having a number in the range, both ends inclusive
+    Range { min: Option<Decimal>, max: Option<Decimal> },
+}
+
+/// A listing filter on one attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeCondition {
+    pub attribute_id: Uuid,
+    pub matches: AttributeMatch,
+}
+
+/// The conditions of an `?attr=` filter, checked against the filterable
+/// attributes
+pub fn parse_attribute_filter(spec: &str, schema: &[AttributeWithOptions]) -> Result<Vec<AttributeCondition>> {
+    let mut conditions: Vec<AttributeCondition> = Vec::new();
+    for part in spec.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (code, values) = part
+            .split_once(':')
+            .ok_or_else(|| Error::validation(format!("Attribute filter '{}' must be code:values", part)))?;
+        let code = code.trim().to_lowercase();
+        let attribute = schema
+            .iter()
+            .find(|a| a.attribute.code == code && a.attribute.filterable && a.attribute.attribute_type != AttributeType::Text)
+            .ok_or_else(|| Error::validation(format!("Products can't be filtered by attribute '{}'", code)))?;
+        if conditions.iter().any(|c| c.attribute_id == attribute.attribute.id) {
+            return Err(Error::validation(format!("Attribute '{}' is filtered by twice", code)));
+        }
+        let invalid = || Error::validation(format!("Invalid filter value for attribute '{}'", code));
+        let values = values.trim();
+        let matches = match attribute.attribute.attribute_type {
+            AttributeType::Select | AttributeType::MultiSelect => {
+                let mut options = Vec::new();
+                for value in values.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                    let option = attribute
+                        .option(value)
+                        .ok_or_else(|| Error::validation(format!("'{}' is not an option of attribute '{}'", value, code)))?;
+                    if !options.contains(&option.id) {
+                        options.push(option.id);
+                    }
+                }
+                if options.is_empty() {
+                    return Err(invalid());
+                }
+                AttributeMatch::Options(options)
+            }
+            AttributeType::Boolean => AttributeMatch::Boolean(values.parse().map_err(|_| invalid())?),
+            AttributeType::Number => {
+                let bound = |s: &str| -> Result<Option<Decimal>> {
+                    let s = s.trim();
+                    if s.is_empty() {
+                        Ok(None)
+                    } else {
+                        s.parse().map(Some).map_err(|_| invalid())
+                    }
+                };
+                let (min, max) = match values.split_once("..") {
+                    Some((min, max)) => (bound(min)?, bound(max)?),
+                    None => (bound(values)?, bound(values)?),
+                };
+                if min.is_none() && max.is_none() {
+                    return Err(invalid());
+                }
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        return Err(invalid());
+                    }
+                }
+                AttributeMatch::Range { min, max }
+            }
+            AttributeType::Text => unreachable!("text attributes are never filterable"),
+        };
+        conditions.push(AttributeCondition { attribute_id: attribute.attribute.id, matches });
+    }
+    if conditions.len() > MAX_ATTRIBUTE_CONDITIONS {
+        return Err(Error::validation(format!(
+            "Products can be filtered by at most {} attributes",
+            MAX_ATTRIBUTE_CONDITIONS
+        )));
+    }
+    Ok(conditions)
+}
+
+/// Check an attribute code and option values before they are stored
+pub fn check_attribute_input(code: Option<&str>, attribute_type: AttributeType, options: &[AttributeOptionInput]) -> Result<()> {
+    if let Some(code) = code {
+        if !code.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            return Err(Error::validation("Attribute codes may only hold a-z, 0-9 and _"));
+        }
+    }
+    if !attribute_type.has_options() {
+        if !options.is_empty() {
+            return Err(Error::validation("Only select and multi_select attributes have options"));
+        }
+        return Ok(());
+    }
+    let mut seen: Vec<String> = Vec::with_capacity(options.len());
+    for option in options {
+        let value = option.value.trim();
+        if value.is_empty() || value.contains(&FILTER_SEPARATORS[..]) {
+            return Err(Error::validation(format!("Invalid option value '{}'", option.value)));
+        }
+        if seen.contains(&value.to_lowercase()) {
+            return Err(Error::validation(format!("Option '{}' is listed twice", value)));
+        }
+        seen.push(value.to_lowercase());
+        if let Some(swatch) = &option.swatch {
+            let hex = swatch.strip_prefix('#').unwrap_or_default();
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::validation(format!("Swatch of option '{}' must be a hex color like #ff0000", value)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Products of a listing per attribute value, for facet counts
+///
+/// One row per option of select attributes, per value of boolean ones and
+/// per attribute for numbers (with their range).
+#[derive(Debug, Clone, FromRow)]
+pub struct AttributeFacetCount {
+    pub attribute_id: Uuid,
+    pub option_id: Option<Uuid>,
+    pub boolean_value: Option<bool>,
+    pub products: i64,
+    pub min_number: Option<Decimal>,
+    pub max_number: Option<Decimal>,
+}
+
+/// A value of a facet with the products it would list
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AttributeFacetValue {
+    /// The option value, or `true`/`false`
+    pub value: String,
+    pub swatch: Option<String>,
+    pub count: i64,
+    /// Whether the listing is filtered by it
+    pub selected: bool,
+}
+
+/// A filterable attribute of a listing with the values to narrow it by
+///
+/// Counts of an attribute the listing is filtered by ignore its own
+/// condition, so picking another of its values adds to the products.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AttributeFacet {
+    pub code: String,
+    pub name: String,
+    pub attribute_type: AttributeType,
+    pub unit: Option<String>,
+    /// Options and boolean values with products; empty for numbers
+    pub values: Vec<AttributeFacetValue>,
+    /// Range of number attributes
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+}
+
+impl AttributeFacet {
+    /// The facet of an attribute from its counts; None if no product of
+    /// the listing has a value of it
+    pub fn build(attribute: &AttributeWithOptions, counts: &[AttributeFacetCount], condition: Option<&AttributeCondition>) -> Option<Self> {
+        let id = attribute.attribute.id;
+        let counts: Vec<_> = counts.iter().filter(|c| c.attribute_id == id).collect();
+        let selected_options = match condition.map(|c| &c.matches) {
+            Some(AttributeMatch::Options(options)) => options.as_slice(),
+            _ => &[],
+        };
+        let mut facet = AttributeFacet {
+            code: attribute.attribute.code.clone(),
+            name: attribute.attribute.name.clone(),
+            attribute_type: attribute.attribute.attribute_type,
+            unit: attribute.attribute.unit.clone(),
+            values: Vec::new(),
+            min: None,
+            max: None,
+        };
+        match attribute.attribute.attribute_type {
+            AttributeType::Text => return None,
+            AttributeType::Number => {
+                let count = counts.first()?;
+                facet.min = count.min_number.map(|n| n.normalize());
+                facet.max = count.max_number.map(|n| n.normalize());
+            }
+            AttributeType::Boolean => {
+                let by_value: HashMap<bool, i64> =
+                    counts.iter().filter_map(|c| Some((c.boolean_value?, c.products))).collect();
+                for flag in [true, false] {
+                    let selected = condition.map(|c| c.matches == AttributeMatch::Boolean(flag)).unwrap_or(false);
+                    let count = by_value.get(&flag).copied().unwrap_or(0);
+                    if count > 0 || selected {
+                        facet.values.push(AttributeFacetValue { value: flag.to_string(), swatch: None, count, selected });
+                    }
+                }
+            }
+            AttributeType::Select | AttributeType::MultiSelect => {
+                let by_option: HashMap<Uuid, i64> =
+                    counts.iter().filter_map(|c| Some((c.option_id?, c.products))).collect();
+                for option in &attribute.options {
+                    let selected = selected_options.contains(&option.id);
+                    let count = by_option.get(&option.id).copied().unwrap_or(0);
+                    if count > 0 || selected {
+                        facet.values.push(AttributeFacetValue {
+                            value: option.value.clone(),
+                            swatch: option.swatch.clone(),
+                            count,
+                            selected,
+                        });
+                    }
+                }
+            }
+        }
+        if facet.values.is_empty() && facet.min.is_none() {
+            return None;
+        }
+        Some(facet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn attribute(code: &str, attribute_type: AttributeType, options: &[&str]) -> AttributeWithOptions {
+        let id = Uuid::new_v4();
+        AttributeWithOptions {
+            attribute: Attribute {
+                id,
+                code: code.to_string(),
+                name: code.to_string(),
+                attribute_type,
+                unit: None,
+                filterable: attribute_type != AttributeType::Text,
+                position: 0,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            options: options
+                .iter()
+                .enumerate()
+                .map(|(position, value)| AttributeOption {
+                    id: Uuid::new_v4(),
+                    attribute_id: id,
+                    value: value.to_string(),
+                    swatch: None,
+                    position: position as i32,
+                    created_at: Utc::now(),
+                })
+                .collect(),
+        }
+    }
+
+    fn schema() -> Vec<AttributeWithOptions> {
+        vec![
+            attribute("color", AttributeType::Select, &["Red", "Blue", "Green"]),
+            attribute("material", AttributeType::MultiSelect, &["Cotton", "Wool"]),
+            attribute("width", AttributeType::Number, &[]),
+            attribute("waterproof", AttributeType::Boolean, &[]),
+            attribute("care", AttributeType::Text, &[]),
+        ]
+    }
+
+    fn request(values: serde_json::Value) -> SetAttributeValuesRequest {
+        serde_json::from_value(serde_json::json!({ "values": values })).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_attribute_values() {
+        let schema = schema();
+        let values = resolve_attribute_values(
+            &schema,
+            &request(serde_json::json!({
+                "color": "red",
+                "material": ["Wool", "cotton", "wool"],
+                "width": 12.5,
+                "waterproof": true,
+                "care": " Hand wash ",
+            })),
+        )
+        .unwrap();
+        assert_eq!(values.len(), 6);
+        let color = values.iter().find(|v| v.attribute_id == schema[0].attribute.id).unwrap();
+        assert_eq!(color.option_id, Some(schema[0].options[0].id));
+        assert_eq!(values.iter().filter(|v| v.attribute_id == schema[1].attribute.id).count(), 2);
+        assert!(values.iter().any(|v| v.number_value == Some(dec!(12.5))));
+        assert!(values.iter().any(|v| v.text_value.as_deref() == Some("Hand wash")));
+
+        for invalid in [
+            serde_json::json!({ "size": "M" }),
+            serde_json::json!({ "color": "Purple" }),
+            serde_json::json!({ "color": ["Red"] }),
+            serde_json::json!({ "width": "wide" }),
+            serde_json::json!({ "waterproof": "yes" }),
+        ] {
+            assert!(resolve_attribute_values(&schema, &request(invalid)).is_err());
+        }
+        assert!(resolve_attribute_values(&schema, &request(serde_json::json!({ "color": null }))).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_attribute_filter() {
+        let schema = schema();
+        let conditions = parse_attribute_filter("color:red,BLUE; width:10..20;waterproof:false", &schema).unwrap();
+        assert_eq!(conditions.len(), 3);
+        assert_eq!(
+            conditions[0].matches,
+            AttributeMatch::Options(vec![schema[0].options[0].id, schema[0].options[1].id])
+        );
+        assert_eq!(conditions[1].matches, AttributeMatch::Range { min: Some(dec!(10)), max: Some(dec!(20)) });
+        assert_eq!(conditions[2].matches, AttributeMatch::Boolean(false));
+
+        let open = parse_attribute_filter("width:..5", &schema).unwrap();
+        assert_eq!(open[0].matches, AttributeMatch::Range { min: None, max: Some(dec!(5)) });
+        assert!(parse_attribute_filter("", &schema).unwrap().is_empty());
+
+        for invalid in ["color", "size:m", "care:wash", "color:purple", "width:20..10", "width:..", "color:red;color:blue"] {
+            assert!(parse_attribute_filter(invalid, &schema).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_check_attribute_input() {
+        let option = |value: &str, swatch: Option<&str>| AttributeOptionInput {
+            value: value.to_string(),
+            swatch: swatch.map(str::to_string),
+        };
+        assert!(check_attribute_input(Some("shoe_size"), AttributeType::Select, &[option("Red", Some("#ff0000"))]).is_ok());
+        assert!(check_attribute_input(Some("Shoe Size"), AttributeType::Select, &[]).is_err());
+        assert!(check_attribute_input(None, AttributeType::Number, &[option("1", None)]).is_err());
+        assert!(check_attribute_input(None, AttributeType::Select, &[option("a,b", None)]).is_err());
+        assert!(check_attribute_input(None, AttributeType::Select, &[option("Red", None), option("red", None)]).is_err());
+        assert!(check_attribute_input(None, AttributeType::Select, &[option("Red", Some("red"))]).is_err());
+    }
+
+    #[test]
+    fn test_facet_build() {
+        let schema = schema();
+        let color = &schema[0];
+        let count = |option_id: Option<Uuid>, boolean_value: Option<bool>, products: i64| AttributeFacetCount {
+            attribute_id: color.attribute.id,
+            option_id,
+            boolean_value,
+            products,
+            min_number: None,
+            max_number: None,
+        };
+        let counts = vec![count(Some(color.options[0].id), None, 4), count(Some(color.options[2].id), None, 1)];
+        let condition = AttributeCondition {
+            attribute_id: color.attribute.id,
+            matches: AttributeMatch::Options(vec![color.options[1].id]),
+        };
+
+        let facet = AttributeFacet::build(color, &counts, Some(&condition)).unwrap();
+        let values: Vec<_> = facet.values.iter().map(|v| (v.value.as_str(), v.count, v.selected)).collect();
+        // Selected options stay listed without products
+        assert_eq!(values, vec![("Red", 4, false), ("Blue", 0, true), ("Green", 1, false)]);
+        assert!(AttributeFacet::build(color, &[], None).is_none());
+
+        let width = &schema[2];
+        let range = AttributeFacetCount {
+            attribute_id: width.attribute.id,
+            min_number: Some(dec!(10.0000)),
+            max_number: Some(dec!(42.5000)),
+            ..count(None, None, 3)
+        };
+        let facet = AttributeFacet::build(width, &[range], None).unwrap();
+        assert_eq!((facet.min, facet.max), (Some(dec!(10)), Some(dec!(42.5))));
+    }
+
+    #[test]
+    fn test_assigned_attributes() {
+        let schema = schema();
+        let value = |attribute: &AttributeWithOptions, option: Option<usize>, number: Option<Decimal>| ProductAttributeValue {
+            id: Uuid::new_v4(),
+            product_id: Uuid::nil(),
+            variant_id: None,
+            attribute_id: attribute.attribute.id,
+            option_id: option.map(|i| attribute.options[i].id),
+            text_value: None,
+            number_value: number,
+            boolean_value: None,
+            created_at: Utc::now(),
+        };
+        let values = [
+            value(&schema[1], Some(1), None),
+            value(&schema[1], Some(0), None),
+            value(&schema[2], None, Some(dec!(12.5000))),
+            value(&schema[0], Some(2), None),
+        ];
+        let assigned = assigned_attributes(&schema, &values.iter().collect::<Vec<_>>());
+        let shown: Vec<_> = assigned.iter().map(|a| (a.code.as_str(), a.value.clone())).collect();
+        assert_eq!(
+            shown,
+            vec![
+                ("color", serde_json::json!("Green")),
+                ("material", serde_json::json!(["Cotton", "Wool"])),
+                ("width", serde_json::json!(dec!(12.5))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Audit log
+//!
+//! One entry per mutating API call: who made it, what it touched and, where
+//! the resource can be snapshotted, which fields changed from what to what.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Fields left out of diffs; they change on every write
+const UNDIFFED_FIELDS: &[&str] = &["updated_at"];
+
+/// Who made an audited call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActorType {
+    /// A customer or staff member, by JWT or session
+    User,
+    ApiKey,
+}
+
+impl AuditActorType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditActorType::User => "user",
+            AuditActorType::ApiKey => "api_key",
+        }
+    }
+}
+
+/// An audited call
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor_type: String,
+    /// Customer/staff ID, or API key ID
+    pub actor_id: Option<Uuid>,
+    /// Email of the user, or name of the API key
+    pub actor_label: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Route template, e.g. `/api/v1/products/:id`
+    pub route: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub status: i16,
+    /// `{"field": {"before": ..., "after": ...}}`
+    pub changes: Option<Value>,
+    pub client_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A call to audit
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub actor_type: AuditActorType,
+    pub actor_id: Option<Uuid>,
+    pub actor_label: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub route: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub status: u16,
+    pub changes: Option<Value>,
+    pub client_ip: Option<String>,
+}
+
+/// Audit log search; all conditions must match
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    /// `user` or `api_key`
+    pub actor_type: Option<String>,
+    /// e.g. `products`
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub method: Option<String>,
+    /// Paths starting with this
+    pub path: Option<String>,
+    /// Only entries that changed this field, e.g. `price`
+    pub field: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Entries older than this id, for paging back
+    pub before_id: Option<i64>,
+}
+
+/// Fields that changed between two snapshots of a row, as
+/// `{"field": {"before": ..., "after": ...}}`. A missing snapshot (the row
+/// was created or deleted) counts as every field being null. None when
+/// nothing changed.
+pub fn diff_snapshots(before: Option<&Value>, after: Option<&Value>) -> Option<Value> {
+    let empty = Map::new();
+    let fields = |snapshot: Option<&Value>| match snapshot {
+        Some(Value::Object(map)) => Some(map.clone()),
+        _ => None,
+    };
+    let (before, after) = (fields(before), fields(after));
+    if before.is_none() && after.is_none() {
+        return None;
+    }
+    let (before, after) = (before.as_ref().unwrap_or(&empty), after.as_ref().unwrap_or(&empty));
+
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key))) {
+        if UNDIFFED_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let old = before.get(key).unwrap_or(&Value::Null);
+        let new = after.get(key).unwrap_or(&Value::Null);
+        if old != new {
+            let mut change = Map::new();
+            change.insert("before".to_string(), old.clone());
+            change.insert("after".to_string(), new.clone());
+            changes.insert(key.clone(), Value::Object(change));
+        }
+    }
+    (!changes.is_empty()).then_some(Value::Object(changes))
+}
+
+/// Whether a field holds a credential (password hash, secret, token) whose
+/// value must not be copied into the audit log
+pub fn is_credential_field(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.contains("password")
+        || name.contains("secret")
+        || name.ends_with("token")
+        || name.ends_with("_hash")
+        || name == "code_verifier"
+        || name == "private_key"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let before = json!({"id": 1, "price": "10.00", "title": "Mug", "updated_at": "a"});
+        let after = json!({"id": 1, "price": "12.50", "title": "Mug", "updated_at": "b"});
+        assert_eq!(
+            diff_snapshots(Some(&before), Some(&after)),
+            Some(json!({"price": {"before": "10.00", "after": "12.50"}}))
+        );
+        assert_eq!(diff_snapshots(Some(&before), Some(&before)), None);
+        assert_eq!(
+            diff_snapshots(None, Some(&json!({"id": 1}))),
+            Some(json!({"id": {"before": null, "after": 1}}))
+        );
+        assert_eq!(diff_snapshots(None, None), None);
+    }
+
+    #[test]
+    fn test_credential_fields() {
+        assert!(is_credential_field("password_hash"));
+        assert!(is_credential_field("client_secret"));
+        assert!(is_credential_field("refresh_token"));
+        assert!(is_credential_field("key_hash"));
+        assert!(!is_credential_field("price"));
+        assert!(!is_credential_field("email"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Automation rule models
+//!
+//! A rule runs its actions when an event of its trigger matches its
+//! conditions (see `crate::automation`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::automation::{AutomationAction, Condition, ConditionTrace};
+
+/// Event a rule runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "automation_trigger", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AutomationTrigger {
+    OrderCreated,
+    /// `event.old_status` and `event.new_status` hold the change
+    OrderStatusChanged,
+    OrderPaid,
+    PaymentFailed,
+    OrderRefunded,
+    CustomerCreated,
+}
+
+impl AutomationTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutomationTrigger::OrderCreated => "order_created",
+            AutomationTrigger::OrderStatusChanged => "order_status_changed",
+            AutomationTrigger::OrderPaid => "order_paid",
+            AutomationTrigger::PaymentFailed => "payment_failed",
+            AutomationTrigger::OrderRefunded => "order_refunded",
+            AutomationTrigger::CustomerCreated => "customer_created",
+        }
+    }
+
+    /// Whether the trigger's events are about an order
+    pub fn has_order(&self) -> bool {
+        !matches!(self, AutomationTrigger::CustomerCreated)
+    }
+}
+
+impl fmt::Display for AutomationTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AutomationTrigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "order_created" => Ok(AutomationTrigger::OrderCreated),
+            "order_status_changed" => Ok(AutomationTrigger::OrderStatusChanged),
+            "order_paid" => Ok(AutomationTrigger::OrderPaid),
+            "payment_failed" => Ok(AutomationTrigger::PaymentFailed),
+            "order_refunded" => Ok(AutomationTrigger::OrderRefunded),
+            "customer_created" => Ok(AutomationTrigger::CustomerCreated),
+            _ => Err(format!("Unknown automation trigger: {}", s)),
+        }
+    }
+}
+
+/// An automation rule
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AutomationRule {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub trigger: AutomationTrigger,
+    pub conditions: Json<Condition>,
+    pub actions: Json<Vec<AutomationAction>>,
+    pub enabled: bool,
+    /// Rules for the same trigger run by ascending position
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a rule
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateAutomationRuleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub description: Option<String>,
+    pub trigger: AutomationTrigger,
+    /// Defaults to always matching
+    #[serde(default)]
+    pub conditions: Condition,
+    pub actions: Vec<AutomationAction>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub position: i32,
+}
+
+/// Request to change a rule; omitted fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateAutomationRuleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub trigger: Option<AutomationTrigger>,
+    pub conditions: Option<Condition>,
+    pub actions: Option<Vec<AutomationAction>>,
+    pub enabled: Option<bool>,
+    pub position: Option<i32>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// An order or customer event queued for the rules of its trigger
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AutomationEvent {
+    pub id: Uuid,
+    pub trigger: AutomationTrigger,
+    pub order_id: Option<Uuid>,
+    pub customer_id: Option<Uuid>,
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one action of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionOutcome {
+    pub action: AutomationAction,
+    pub succeeded: bool,
+    pub detail: String,
+}
+
+/// A rule that matched an event, and what its actions did
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AutomationRun {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub event_id: Uuid,
+    pub trigger: AutomationTrigger,
+    pub order_id: Option<Uuid>,
+    pub customer_id: Option<Uuid>,
+    /// succeeded, or failed if any action failed
+    pub status: String,
+    pub actions: Json<Vec<ActionOutcome>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Subject of a dry run; give the order for order triggers and the
+/// customer for customer triggers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunSubject {
+    pub order_id: Option<Uuid>,
+    pub customer_id: Option<Uuid>,
+    /// Stand-in for the event details, e.g. `{"old_status": "pending", "new_status": "confirmed"}`
+    #[serde(default)]
+    pub event: Option<Value>,
+}
+
+/// Request to try an unsaved rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestAutomationRuleRequest {
+    pub rule: CreateAutomationRuleRequest,
+    #[serde(flatten)]
+    pub subject: DryRunSubject,
+}
+
+/// What a rule would do for an order or customer, without doing it
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationDryRun {
+    pub matched: bool,
+    /// Every comparison with the value it saw
+    pub conditions: Vec<ConditionTrace>,
+    /// Actions that would run, in order (empty unless matched)
+    pub actions: Vec<String>,
+    /// The context the rule was evaluated against
+    pub context: Value,
+}
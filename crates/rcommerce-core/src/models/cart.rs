@@ -11,7 +11,7 @@ use uuid::Uuid;
 use validator::Validate;
 
 /// Cart entity - represents a shopping cart
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
 pub struct Cart {
     pub id: Uuid,
     /// Optional customer ID (null for guest carts)
@@ -55,7 +55,7 @@ pub struct Cart {
 }
 
 /// Cart item - represents a product in the cart
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
 pub struct CartItem {
     pub id: Uuid,
     pub cart_id: Uuid,
@@ -133,7 +133,7 @@ pub struct CartSummary {
 }
 
 /// Cart with items for API responses
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct CartWithItems {
     pub cart: Cart,
     pub items: Vec<CartItem>,
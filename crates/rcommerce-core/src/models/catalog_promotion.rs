@@ -0,0 +1,300 @@
+//! Catalog promotion models
+//!
+//! A catalog snapshot is an instance's merchandising data keyed by natural
+//! keys (slugs and codes) rather than IDs, so a staging and a production
+//! instance can be compared. Diffing two snapshots gives the changes that
+//! make the target match the source; approved changes are then applied to
+//! the target. See `crate::services::CatalogPromotionService`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::shipping::ShippingRule;
+use crate::{Error, Result};
+
+/// Kind of catalog record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogEntity {
+    TaxCategory,
+    TaxZone,
+    Category,
+    Product,
+    TaxRate,
+    ShippingRule,
+}
+
+impl CatalogEntity {
+    /// All entities, in the order changes are applied (records before the
+    /// records that reference them)
+    pub const ALL: [CatalogEntity; 6] = [
+        CatalogEntity::TaxCategory,
+        CatalogEntity::TaxZone,
+        CatalogEntity::Category,
+        CatalogEntity::Product,
+        CatalogEntity::TaxRate,
+        CatalogEntity::ShippingRule,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatalogEntity::TaxCategory => "tax_category",
+            CatalogEntity::TaxZone => "tax_zone",
+            CatalogEntity::Category => "category",
+            CatalogEntity::Product => "product",
+            CatalogEntity::TaxRate => "tax_rate",
+            CatalogEntity::ShippingRule => "shipping_rule",
+        }
+    }
+}
+
+impl std::fmt::Display for CatalogEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for CatalogEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        CatalogEntity::ALL
+            .into_iter()
+            .find(|entity| entity.as_str() == s)
+            .ok_or_else(|| format!("Unknown catalog entity '{}'", s))
+    }
+}
+
+/// A record in a catalog snapshot
+pub trait CatalogRecord: Serialize + DeserializeOwned + PartialEq {
+    const ENTITY: CatalogEntity;
+
+    /// Natural key, the same on every instance
+    fn key(&self) -> String;
+}
+
+/// Product category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct CatalogCategory {
+    pub slug: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub parent_slug: Option<String>,
+    pub image_url: Option<String>,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    pub sort_order: i32,
+    pub is_active: bool,
+}
+
+impl CatalogRecord for CatalogCategory {
+    const ENTITY: CatalogEntity = CatalogEntity::Category;
+
+    fn key(&self) -> String {
+        self.slug.clone()
+    }
+}
+
+/// Product merchandising fields; stock levels are per instance and are not
+/// promoted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct CatalogProduct {
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    pub product_type: String,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub cost_price: Option<Decimal>,
+    pub currency: String,
+    pub weight: Option<Decimal>,
+    pub weight_unit: Option<String>,
+    pub requires_shipping: bool,
+    pub is_active: bool,
+    pub is_featured: bool,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    pub tax_category_code: Option<String>,
+    /// Category slugs, sorted
+    pub category_slugs: Vec<String>,
+    pub primary_category_slug: Option<String>,
+}
+
+impl CatalogRecord for CatalogProduct {
+    const ENTITY: CatalogEntity = CatalogEntity::Product;
+
+    fn key(&self) -> String {
+        self.slug.clone()
+    }
+}
+
+/// Tax zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct CatalogTaxZone {
+    pub code: String,
+    pub name: String,
+    pub country_code: String,
+    pub region_code: Option<String>,
+    pub postal_code_pattern: Option<String>,
+    pub zone_type: String,
+    pub parent_code: Option<String>,
+}
+
+impl CatalogRecord for CatalogTaxZone {
+    const ENTITY: CatalogEntity = CatalogEntity::TaxZone;
+
+    fn key(&self) -> String {
+        self.code.clone()
+    }
+}
+
+/// Tax category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct CatalogTaxCategory {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_digital: bool,
+    pub is_food: bool,
+    pub is_luxury: bool,
+    pub is_medical: bool,
+    pub is_educational: bool,
+}
+
+impl CatalogRecord for CatalogTaxCategory {
+    const ENTITY: CatalogEntity = CatalogEntity::TaxCategory;
+
+    fn key(&self) -> String {
+        self.code.clone()
+    }
+}
+
+/// Tax rate, keyed by zone, category and start date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct CatalogTaxRate {
+    pub zone_code: String,
+    /// None for the zone's default rate
+    pub category_code: Option<String>,
+    pub valid_from: NaiveDate,
+    pub name: String,
+    pub rate: Decimal,
+    pub rate_type: String,
+    pub is_vat: bool,
+    pub vat_type: Option<String>,
+    pub b2b_exempt: bool,
+    pub reverse_charge: bool,
+    pub valid_until: Option<NaiveDate>,
+    pub priority: i32,
+}
+
+impl CatalogRecord for CatalogTaxRate {
+    const ENTITY: CatalogEntity = CatalogEntity::TaxRate;
+
+    fn key(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.zone_code,
+            self.category_code.as_deref().unwrap_or("*"),
+            self.valid_from
+        )
+    }
+}
+
+impl CatalogRecord for ShippingRule {
+    const ENTITY: CatalogEntity = CatalogEntity::ShippingRule;
+
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// An instance's catalog
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogSnapshot {
+    pub generated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tax_categories: Vec<CatalogTaxCategory>,
+    #[serde(default)]
+    pub tax_zones: Vec<CatalogTaxZone>,
+    #[serde(default)]
+    pub categories: Vec<CatalogCategory>,
+    #[serde(default)]
+    pub products: Vec<CatalogProduct>,
+    #[serde(default)]
+    pub tax_rates: Vec<CatalogTaxRate>,
+    #[serde(default)]
+    pub shipping_rules: Vec<ShippingRule>,
+}
+
+/// What a change does to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogChangeAction {
+    Create,
+    Update,
+    /// Products are deactivated instead, since orders reference them
+    Delete,
+}
+
+/// One change to make a target's record match the source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogChange {
+    pub entity: CatalogEntity,
+    pub key: String,
+    pub action: CatalogChangeAction,
+    /// Fields that differ, for updates
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// The source record, for creates and updates
+    #[serde(default)]
+    pub record: Option<serde_json::Value>,
+}
+
+impl CatalogChange {
+    /// The source record of a create or update
+    pub fn decode<T: CatalogRecord>(&self) -> Result<T> {
+        let record = self
+            .record
+            .clone()
+            .ok_or_else(|| Error::validation(format!("{} '{}' has no record", self.entity, self.key)))?;
+        serde_json::from_value(record)
+            .map_err(|e| Error::validation(format!("Invalid {} '{}': {}", self.entity, self.key, e)))
+    }
+}
+
+/// Options for diffing a source catalog against a target
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogDiffOptions {
+    /// Only these entities (all when empty)
+    #[serde(default)]
+    pub entities: Vec<CatalogEntity>,
+    /// Also delete target records missing from the source
+    #[serde(default)]
+    pub include_deletes: bool,
+}
+
+/// Request to diff a source snapshot against this instance
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogDiffRequest {
+    pub source: CatalogSnapshot,
+    #[serde(flatten)]
+    pub options: CatalogDiffOptions,
+}
+
+/// Request to apply approved changes to this instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyCatalogChangesRequest {
+    pub changes: Vec<CatalogChange>,
+}
+
+/// Outcome of applying changes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CatalogApplyResult {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
@@ -0,0 +1,92 @@
+//! Category requests
+//!
+//! Categories ([`super::ProductCategory`]) form a tree: a product listing
+//! filtered by a category includes the products of its subcategories.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Request to create a category
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateCategoryRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    /// Defaults to the name in lower case with dashes
+    #[validate(length(min = 1, max = 255))]
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+/// Request to update a category; `parent_id: null` makes it a root
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateCategoryRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent_id: Option<Option<Uuid>>,
+    pub image_url: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// Products to add to a category or collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignProductsRequest {
+    pub product_ids: Vec<Uuid>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Tell a field set to null (`Some(None)`) from a missing one (`None`)
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A slug from a name: lower case, with runs of other characters as dashes
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Summer Sale"), "summer-sale");
+        assert_eq!(slugify("  Men's T-Shirts & Tops "), "men-s-t-shirts-tops");
+        assert_eq!(slugify("Café"), "café");
+    }
+
+    #[test]
+    fn test_update_parent() {
+        let request: UpdateCategoryRequest = serde_json::from_str(r#"{"parent_id": null}"#).unwrap();
+        assert_eq!(request.parent_id, Some(None));
+        let request: UpdateCategoryRequest = serde_json::from_str(r#"{"name": "Shoes"}"#).unwrap();
+        assert_eq!(request.parent_id, None);
+    }
+}
@@ -0,0 +1,80 @@
+//! Custom checkout field models
+//!
+//! Merchants define extra checkout fields in `[checkout_fields]`; the
+//! storefront discovers them from the schema endpoint and sends the answers
+//! as `custom_fields` with the completed checkout. Answers are kept on the
+//! order, keyed by field key.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Answers to custom checkout fields, by field key
+pub type CheckoutFieldValues = BTreeMap<String, serde_json::Value>;
+
+/// Custom checkout field type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutFieldType {
+    Text,
+    Email,
+    Phone,
+    Number,
+    /// One of `options`
+    Select,
+    /// Stored as a boolean; a required checkbox must be ticked
+    Checkbox,
+    /// "YYYY-MM-DD"
+    Date,
+}
+
+impl CheckoutFieldType {
+    /// Whether answers are strings that length and pattern rules apply to
+    pub fn is_text(&self) -> bool {
+        matches!(self, CheckoutFieldType::Text | CheckoutFieldType::Email | CheckoutFieldType::Phone)
+    }
+}
+
+/// A custom checkout field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckoutFieldDefinition {
+    /// Key the answer is stored under, e.g. "vat_number"
+    pub key: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub field_type: CheckoutFieldType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub help_text: Option<String>,
+    /// Choices for select fields
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Regular expression text answers must match
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Bounds for number fields
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+/// Custom checkout field answers recorded on an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCheckoutFields {
+    pub order_id: Uuid,
+    pub custom_fields: CheckoutFieldValues,
+}
+
+/// Replace an order's custom checkout field answers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateCheckoutFieldsRequest {
+    #[serde(default)]
+    pub custom_fields: CheckoutFieldValues,
+}
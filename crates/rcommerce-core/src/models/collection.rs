@@ -0,0 +1,303 @@
+//! Collection models
+//!
+//! A manual collection holds the products added to it, in their
+//! `position` order. A smart collection holds every product matching its
+//! rules on tag, price or vendor: all of them, or any one when
+//! `disjunctive`.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// How a collection's products are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "collection_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionType {
+    /// Products added by hand
+    #[default]
+    Manual,
+    /// Products matching the rules
+    Smart,
+}
+
+/// Product attribute a smart collection rule tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionRuleField {
+    /// One of the product's tags (case-insensitive)
+    Tag,
+    /// The product's price
+    Price,
+    /// The product's vendor (case-insensitive)
+    Vendor,
+}
+
+impl CollectionRuleField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionRuleField::Tag => "tag",
+            CollectionRuleField::Price => "price",
+            CollectionRuleField::Vendor => "vendor",
+        }
+    }
+}
+
+/// How a rule compares the field with its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionRuleRelation {
+    Equals,
+    NotEquals,
+    GreaterThan,
+    LessThan,
+    Contains,
+}
+
+impl CollectionRuleRelation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionRuleRelation::Equals => "equals",
+            CollectionRuleRelation::NotEquals => "not_equals",
+            CollectionRuleRelation::GreaterThan => "greater_than",
+            CollectionRuleRelation::LessThan => "less_than",
+            CollectionRuleRelation::Contains => "contains",
+        }
+    }
+}
+
+/// A smart collection rule, e.g. tag equals "summer"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CollectionRule {
+    pub field: CollectionRuleField,
+    pub relation: CollectionRuleRelation,
+    pub value: String,
+}
+
+impl CollectionRule {
+    pub fn new(field: CollectionRuleField, relation: CollectionRuleRelation, value: impl Into<String>) -> Self {
+        Self {
+            field,
+            relation,
+            value: value.into(),
+        }
+    }
+
+    /// Check the relation suits the field and the value parses
+    pub fn validate(&self) -> Result<(), String> {
+        use CollectionRuleField::*;
+        use CollectionRuleRelation::*;
+
+        let value = self.value.trim();
+        if value.is_empty() {
+            return Err("Collection rules need a value".to_string());
+        }
+        let allowed = match self.field {
+            Tag => matches!(self.relation, Equals | NotEquals),
+            Price => matches!(self.relation, Equals | NotEquals | GreaterThan | LessThan),
+            Vendor => matches!(self.relation, Equals | NotEquals | Contains),
+        };
+        if !allowed {
+            return Err(format!("{} rules cannot use {}", self.field.as_str(), self.relation.as_str()));
+        }
+        if self.field == Price && value.parse::<Decimal>().is_err() {
+            return Err(format!("Invalid price in collection rule: {}", value));
+        }
+        Ok(())
+    }
+
+    /// The rule as a condition on `products`, with its value bound as
+    /// text to `$param`
+    fn to_sql(&self, param: usize) -> String {
+        use CollectionRuleField::*;
+        use CollectionRuleRelation::*;
+
+        let has_tag = format!(
+            "EXISTS (SELECT 1 FROM product_tag_relations ptr \
+             JOIN product_tags pt ON pt.id = ptr.tag_id \
+             WHERE ptr.product_id = products.id AND LOWER(pt.name) = LOWER(${}))",
+            param
+        );
+        match (self.field, self.relation) {
+            (Tag, NotEquals) => format!("NOT {}", has_tag),
+            (Tag, _) => has_tag,
+            (Price, Equals) => format!("products.price = ${}::NUMERIC", param),
+            (Price, NotEquals) => format!("products.price <> ${}::NUMERIC", param),
+            (Price, GreaterThan) => format!("products.price > ${}::NUMERIC", param),
+            (Price, _) => format!("products.price < ${}::NUMERIC", param),
+            (Vendor, NotEquals) => format!("LOWER(COALESCE(products.vendor, '')) <> LOWER(${})", param),
+            (Vendor, Contains) => format!(
+                "products.vendor ILIKE '%' || REPLACE(REPLACE(REPLACE(${}, '\\', '\\\\'), '%', '\\%'), '_', '\\_') || '%'",
+                param
+            ),
+            (Vendor, _) => format!("LOWER(products.vendor) = LOWER(${})", param),
+        }
+    }
+}
+
+/// Condition on `products` for a smart collection's rules, with the values
+/// to bind from `$first_param` on
+pub fn collection_rules_sql(rules: &[CollectionRule], disjunctive: bool, first_param: usize) -> (String, Vec<String>) {
+    if rules.is_empty() {
+        return ("FALSE".to_string(), Vec::new());
+    }
+    let conditions: Vec<String> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| rule.to_sql(first_param + i))
+        .collect();
+    let joiner = if disjunctive { " OR " } else { " AND " };
+    let values = rules.iter().map(|rule| rule.value.trim().to_string()).collect();
+    (format!("({})", conditions.join(joiner)), values)
+}
+
+/// Orders a collection's products can be listed in
+pub const COLLECTION_SORT_ORDERS: &[&str] = &[
+    "manual",
+    "alpha-asc",
+    "alpha-desc",
+    "created",
+    "created-desc",
+    "price-asc",
+    "price-desc",
+];
+
+/// ORDER BY for a collection's sort order; `manual` sorts by position,
+/// bound to `$collection_param` (smart collections fall back to newest first)
+pub fn collection_order_by(collection: &Collection, collection_param: usize) -> String {
+    match collection.sort_order.as_str() {
+        "manual" if collection.collection_type == CollectionType::Manual => format!(
+            "(SELECT cp.position FROM collection_products cp \
+             WHERE cp.collection_id = ${} AND cp.product_id = products.id), created_at DESC",
+            collection_param
+        ),
+        "alpha-asc" => "title ASC".to_string(),
+        "alpha-desc" => "title DESC".to_string(),
+        "created" => "created_at ASC".to_string(),
+        "price-asc" => "price ASC, created_at DESC".to_string(),
+        "price-desc" => "price DESC, created_at DESC".to_string(),
+        _ => "created_at DESC".to_string(),
+    }
+}
+
+/// Product collection
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct Collection {
+    pub id: Uuid,
+    pub title: String,
+    pub handle: String,
+    pub description: Option<String>,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    /// One of [`COLLECTION_SORT_ORDERS`]
+    pub sort_order: String,
+    /// None while unpublished
+    pub published_at: Option<DateTime<Utc>>,
+    pub template_suffix: Option<String>,
+    /// Smart collections match any rule rather than all of them
+    pub disjunctive: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub published_scope: String, // web, global
+    pub collection_type: CollectionType,
+    #[schema(value_type = Vec<CollectionRule>)]
+    pub rules: Json<Vec<CollectionRule>>,
+}
+
+impl Collection {
+    pub fn is_published(&self) -> bool {
+        self.published_at.is_some_and(|at| at <= Utc::now())
+    }
+}
+
+/// Request to create a collection
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateCollectionRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+    /// Defaults to the title in lower case with dashes
+    #[validate(length(min = 1, max = 255))]
+    pub handle: Option<String>,
+    pub description: Option<String>,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    #[serde(default)]
+    pub collection_type: CollectionType,
+    /// Smart collections only
+    #[serde(default)]
+    pub rules: Vec<CollectionRule>,
+    #[serde(default)]
+    pub disjunctive: bool,
+    /// Defaults to `manual` for manual collections, `created-desc` for smart ones
+    pub sort_order: Option<String>,
+    /// Unpublished collections are hidden from the storefront
+    #[serde(default = "default_true")]
+    pub published: bool,
+}
+
+/// Request to update a collection; its type cannot change
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateCollectionRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub handle: Option<String>,
+    pub description: Option<String>,
+    pub seo_title: Option<String>,
+    pub seo_description: Option<String>,
+    pub rules: Option<Vec<CollectionRule>>,
+    pub disjunctive: Option<bool>,
+    pub sort_order: Option<String>,
+    pub published: Option<bool>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CollectionRuleField::*;
+    use CollectionRuleRelation::*;
+
+    #[test]
+    fn test_rule_validation() {
+        assert!(CollectionRule::new(Tag, Equals, "summer").validate().is_ok());
+        assert!(CollectionRule::new(Price, LessThan, "19.99").validate().is_ok());
+        assert!(CollectionRule::new(Vendor, Contains, "acme").validate().is_ok());
+
+        assert!(CollectionRule::new(Tag, GreaterThan, "summer").validate().is_err());
+        assert!(CollectionRule::new(Price, Contains, "10").validate().is_err());
+        assert!(CollectionRule::new(Price, GreaterThan, "ten").validate().is_err());
+        assert!(CollectionRule::new(Vendor, Equals, "  ").validate().is_err());
+    }
+
+    #[test]
+    fn test_rules_sql() {
+        let rules = vec![
+            CollectionRule::new(Tag, Equals, "summer"),
+            CollectionRule::new(Price, LessThan, " 50 "),
+        ];
+        let (sql, values) = collection_rules_sql(&rules, false, 3);
+        assert!(sql.starts_with("(EXISTS (SELECT 1 FROM product_tag_relations"));
+        assert!(sql.contains("LOWER(pt.name) = LOWER($3)) AND products.price < $4::NUMERIC)"));
+        assert_eq!(values, vec!["summer".to_string(), "50".to_string()]);
+
+        let (sql, _) = collection_rules_sql(&rules, true, 1);
+        assert!(sql.contains(") OR products.price < $2::NUMERIC"));
+
+        assert_eq!(collection_rules_sql(&[], false, 1), ("FALSE".to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn test_rule_json() {
+        let rule: CollectionRule =
+            serde_json::from_str(r#"{"field": "vendor", "relation": "not_equals", "value": "Acme"}"#).unwrap();
+        assert_eq!(rule, CollectionRule::new(Vendor, NotEquals, "Acme"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Store configuration bundle models
+//!
+//! A configuration bundle holds the store setup that is promoted between
+//! instances (staging to production) rather than entered twice: tax
+//! categories, zones and rates, shipping rules, stored email templates and
+//! payment gateway settings. Records are keyed like catalog snapshots, so
+//! importing a bundle is deterministic: the same bundle always leaves the
+//! same setup. Gateway credentials never leave an instance.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::PaymentConfig;
+use crate::models::{CatalogChange, CatalogEntity, CatalogTaxCategory, CatalogTaxRate, CatalogTaxZone};
+use crate::shipping::ShippingRule;
+use crate::{Error, Result};
+
+/// Format version of bundles written by this release
+pub const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// Catalog entities a bundle holds
+pub const CONFIG_BUNDLE_ENTITIES: [CatalogEntity; 4] = [
+    CatalogEntity::TaxCategory,
+    CatalogEntity::TaxZone,
+    CatalogEntity::TaxRate,
+    CatalogEntity::ShippingRule,
+];
+
+/// A store's configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub generated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tax_categories: Vec<CatalogTaxCategory>,
+    #[serde(default)]
+    pub tax_zones: Vec<CatalogTaxZone>,
+    #[serde(default)]
+    pub tax_rates: Vec<CatalogTaxRate>,
+    #[serde(default)]
+    pub shipping_rules: Vec<ShippingRule>,
+    /// Stored email templates, by name
+    #[serde(default)]
+    pub email_templates: Vec<BundleEmailTemplate>,
+    #[serde(default)]
+    pub payment: PaymentSettings,
+}
+
+impl ConfigBundle {
+    /// Bundles from newer releases may hold settings this one would drop
+    pub fn check_version(&self) -> Result<()> {
+        if self.version == 0 || self.version > CONFIG_BUNDLE_VERSION {
+            return Err(Error::validation(format!(
+                "Configuration bundle version {} is not supported (this release reads up to {})",
+                self.version, CONFIG_BUNDLE_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A stored email template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleEmailTemplate {
+    /// Template type from the variables catalog (e.g. "order_confirmation")
+    pub name: String,
+    pub subject_template: String,
+    pub body_template: String,
+    pub html_template: Option<String>,
+    pub is_active: bool,
+}
+
+/// Payment gateway settings without credentials
+///
+/// Gateways are configured in the config file (`[payment]`), so importing
+/// reports the differences to make there instead of changing them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaymentSettings {
+    pub default_gateway: String,
+    pub test_mode: bool,
+    /// Gateways by ID
+    #[serde(default)]
+    pub gateways: BTreeMap<String, GatewaySettings>,
+}
+
+/// Settings of one payment gateway
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GatewaySettings {
+    pub enabled: bool,
+    /// Sandbox or demo environment, for gateways that have one
+    #[serde(default)]
+    pub sandbox: Option<bool>,
+}
+
+impl PaymentSettings {
+    /// Settings of the gateways in `[payment]`
+    pub fn from_config(config: &PaymentConfig) -> Self {
+        let gateways = [
+            ("stripe", GatewaySettings { enabled: config.stripe.enabled, sandbox: None }),
+            ("wechatpay", GatewaySettings { enabled: config.wechatpay.enabled, sandbox: Some(config.wechatpay.sandbox) }),
+            ("alipay", GatewaySettings { enabled: config.alipay.enabled, sandbox: Some(config.alipay.sandbox) }),
+            ("airwallex", GatewaySettings { enabled: config.airwallex.enabled, sandbox: Some(config.airwallex.demo) }),
+        ];
+        Self {
+            default_gateway: config.default_gateway.clone(),
+            test_mode: config.test_mode,
+            gateways: gateways.into_iter().map(|(id, settings)| (id.to_string(), settings)).collect(),
+        }
+    }
+
+    /// Config file changes that would make `current` match these settings,
+    /// e.g. `payment.stripe.enabled = true`
+    pub fn differences(&self, current: &PaymentSettings) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.default_gateway != current.default_gateway {
+            differences.push(format!("payment.default_gateway = \"{}\"", self.default_gateway));
+        }
+        if self.test_mode != current.test_mode {
+            differences.push(format!("payment.test_mode = {}", self.test_mode));
+        }
+        for (id, wanted) in &self.gateways {
+            let Some(have) = current.gateways.get(id) else {
+                differences.push(format!("payment.{}: gateway not supported by this instance", id));
+                continue;
+            };
+            if wanted.enabled != have.enabled {
+                differences.push(format!("payment.{}.enabled = {}", id, wanted.enabled));
+            }
+            if let (Some(wanted), Some(have)) = (wanted.sandbox, have.sandbox) {
+                if wanted != have {
+                    let key = if id == "airwallex" { "demo" } else { "sandbox" };
+                    differences.push(format!("payment.{}.{} = {}", id, key, wanted));
+                }
+            }
+        }
+        differences
+    }
+}
+
+/// What importing a bundle changes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigImportPlan {
+    /// Tax and shipping rule changes
+    pub changes: Vec<CatalogChange>,
+    /// Email templates created or replaced
+    pub email_templates: Vec<String>,
+    /// Config file changes to make by hand
+    pub payment_differences: Vec<String>,
+}
+
+impl ConfigImportPlan {
+    /// Whether importing changes anything in the database
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.email_templates.is_empty()
+    }
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConfigImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub email_templates: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payment_settings_leave_out_credentials() {
+        let mut config = PaymentConfig::default();
+        config.stripe.enabled = true;
+        config.stripe.secret_key = Some("sk_live_secret".to_string());
+        let settings = PaymentSettings::from_config(&config);
+
+        assert!(settings.gateways["stripe"].enabled);
+        assert!(!serde_json::to_string(&settings).unwrap().contains("sk_live_secret"));
+    }
+
+    #[test]
+    fn test_payment_differences() {
+        let current = PaymentSettings::from_config(&PaymentConfig::default());
+        let mut wanted = current.clone();
+        assert!(wanted.differences(&current).is_empty());
+
+        wanted.default_gateway = "stripe".to_string();
+        wanted.gateways.get_mut("stripe").unwrap().enabled = true;
+        wanted.gateways.get_mut("airwallex").unwrap().sandbox = Some(true);
+        assert_eq!(
+            wanted.differences(&current),
+            vec![
+                "payment.default_gateway = \"stripe\"".to_string(),
+                "payment.airwallex.demo = true".to_string(),
+                "payment.stripe.enabled = true".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundle_version() {
+        let mut bundle = ConfigBundle { version: CONFIG_BUNDLE_VERSION, ..ConfigBundle::default() };
+        assert!(bundle.check_version().is_ok());
+        bundle.version = CONFIG_BUNDLE_VERSION + 1;
+        assert!(bundle.check_version().is_err());
+    }
+}
@@ -53,6 +53,8 @@ pub struct Customer {
     pub last_login_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing)]
     pub role: CustomerRole,
+    /// Group whose prices the customer gets
+    pub customer_group_id: Option<Uuid>,
 }
 
 /// Create customer request
@@ -92,6 +94,10 @@ pub struct UpdateCustomerRequest {
 /// Create address request
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateAddressRequest {
+    /// Name shown in the address book, e.g. "Home"
+    #[validate(length(min = 1, max = 50))]
+    pub label: Option<String>,
+    
     #[validate(length(min = 1, max = 100))]
     pub first_name: String,
     
@@ -121,6 +127,8 @@ pub struct CreateAddressRequest {
     #[validate(length(min = 1, max = 20))]
     pub zip: String,
     
+    #[serde(default)]
     pub is_default_shipping: bool,
+    #[serde(default)]
     pub is_default_billing: bool,
 }
\ No newline at end of file
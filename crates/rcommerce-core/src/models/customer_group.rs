@@ -0,0 +1,171 @@
+//! Customer group models
+//!
+//! A customer belongs to at most one group (wholesale, VIP, retail, ...).
+//! The group's own prices for a product or variant, in quantity tiers,
+//! replace its regular price; everything else gets the group's percentage
+//! discount. Group prices are in the product's own currency.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// A group of customers with negotiated prices
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CustomerGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// Percentage taken off prices the group has no price of its own for
+    pub discount_percent: Decimal,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A group's price for a product or variant from a quantity up
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CustomerGroupPrice {
+    pub id: Uuid,
+    pub customer_group_id: Uuid,
+    pub product_id: Uuid,
+    /// None prices the product and all of its variants
+    pub variant_id: Option<Uuid>,
+    /// Smallest quantity the price applies to
+    pub min_quantity: i32,
+    pub price: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a customer group
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateCustomerGroupRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub discount_percent: Decimal,
+}
+
+/// Request to update a customer group
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateCustomerGroupRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub discount_percent: Option<Decimal>,
+    pub is_active: Option<bool>,
+}
+
+/// Request to set a group's price for a product or variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetCustomerGroupPriceRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    /// 1 (default) for the price at any quantity
+    pub min_quantity: Option<i32>,
+    pub price: Decimal,
+}
+
+/// Request to move a customer into a group, or out of theirs with None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignCustomerGroupRequest {
+    pub customer_group_id: Option<Uuid>,
+}
+
+/// A customer's group and its prices for the products being priced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerGroupPricing {
+    pub group: CustomerGroup,
+    pub prices: Vec<CustomerGroupPrice>,
+}
+
+impl CustomerGroupPricing {
+    /// The group's price for a quantity: the highest tier reached, a
+    /// variant's own tiers before its product's
+    pub fn price_for(&self, product_id: Uuid, variant_id: Option<Uuid>, quantity: i32) -> Option<&CustomerGroupPrice> {
+        let best = |variant: Option<Uuid>| {
+            self.prices
+                .iter()
+                .filter(|p| p.product_id == product_id && p.variant_id == variant && p.min_quantity <= quantity)
+                .max_by_key(|p| p.min_quantity)
+        };
+        variant_id.and_then(|variant| best(Some(variant))).or_else(|| best(None))
+    }
+
+    /// A price less the group's discount
+    pub fn discounted(&self, price: Decimal) -> Decimal {
+        if self.group.discount_percent.is_zero() {
+            return price;
+        }
+        (price * (Decimal::ONE_HUNDRED - self.group.discount_percent) / Decimal::ONE_HUNDRED).round_dp(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn group_price(product_id: Uuid, variant_id: Option<Uuid>, min_quantity: i32, price: Decimal) -> CustomerGroupPrice {
+        CustomerGroupPrice {
+            id: Uuid::new_v4(),
+            customer_group_id: Uuid::nil(),
+            product_id,
+            variant_id,
+            min_quantity,
+            price,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn pricing(discount_percent: Decimal, prices: Vec<CustomerGroupPrice>) -> CustomerGroupPricing {
+        CustomerGroupPricing {
+            group: CustomerGroup {
+                id: Uuid::nil(),
+                name: "Wholesale".to_string(),
+                description: None,
+                discount_percent,
+                is_active: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            prices,
+        }
+    }
+
+    #[test]
+    fn test_price_for_tiers() {
+        let product = Uuid::new_v4();
+        let variant = Uuid::new_v4();
+        let pricing = pricing(
+            dec!(0),
+            vec![
+                group_price(product, None, 1, dec!(20.00)),
+                group_price(product, None, 10, dec!(18.00)),
+                group_price(product, None, 50, dec!(15.00)),
+                group_price(product, Some(variant), 5, dec!(17.00)),
+            ],
+        );
+        let price = |variant_id, quantity| pricing.price_for(product, variant_id, quantity).map(|p| p.price);
+
+        assert_eq!(price(None, 1), Some(dec!(20.00)));
+        assert_eq!(price(None, 49), Some(dec!(18.00)));
+        assert_eq!(price(None, 50), Some(dec!(15.00)));
+        // Variant tiers first, then the product's
+        assert_eq!(price(Some(variant), 5), Some(dec!(17.00)));
+        assert_eq!(price(Some(variant), 4), Some(dec!(20.00)));
+        assert!(pricing.price_for(Uuid::new_v4(), None, 100).is_none());
+    }
+
+    #[test]
+    fn test_discounted() {
+        assert_eq!(pricing(dec!(15), vec![]).discounted(dec!(29.99)), dec!(25.49));
+        assert_eq!(pricing(dec!(0), vec![]).discounted(dec!(29.99)), dec!(29.99));
+        assert_eq!(pricing(dec!(100), vec![]).discounted(dec!(29.99)), dec!(0));
+    }
+}
@@ -0,0 +1,62 @@
+//! Product customs data
+//!
+//! The HS code and country of origin of a product, used to estimate duties
+//! of cross-border orders at checkout and to fill customs declarations on
+//! shipping labels.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Customs data of a product
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductCustoms {
+    pub product_id: Uuid,
+    /// Harmonized System code, 6 to 10 digits
+    pub hs_code: Option<String>,
+    /// ISO country the product is made in
+    pub origin_country: Option<String>,
+    /// What customs declarations call it (default the item title)
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to set the customs data of a product
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SetProductCustomsRequest {
+    #[validate(length(min = 6, max = 10), custom = "validate_hs_code")]
+    pub hs_code: Option<String>,
+    #[validate(length(equal = 2))]
+    pub origin_country: Option<String>,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+}
+
+fn validate_hs_code(hs_code: &str) -> Result<(), validator::ValidationError> {
+    if hs_code.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(validator::ValidationError::new("hs_code_digits"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_product_customs_validation() {
+        let request = |hs_code: &str, origin: &str| SetProductCustomsRequest {
+            hs_code: Some(hs_code.to_string()),
+            origin_country: Some(origin.to_string()),
+            description: None,
+        };
+        assert!(request("610910", "PT").validate().is_ok());
+        assert!(request("6109.10", "PT").validate().is_err());
+        assert!(request("6109", "PT").validate().is_err());
+        assert!(request("610910", "PRT").validate().is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Exchange rate model
+//!
+//! Rates are stored historically and never updated in place, so reports can
+//! always use the rate that applied when an order or payment was created.
+//! A rate means "1 unit of `quote_currency` = `rate` units of `base_currency`".
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::Currency;
+
+/// A historical exchange rate
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub base_currency: Currency,
+    pub quote_currency: Currency,
+    pub rate: Decimal,
+    pub source: String,
+    pub effective_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExchangeRate {
+    /// Convert an amount in the quote currency to the base currency
+    pub fn to_base(&self, amount: Decimal) -> Decimal {
+        (amount * self.rate).round_dp(2)
+    }
+}
+
+/// Request to record a new rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordExchangeRateRequest {
+    pub base_currency: Currency,
+    pub quote_currency: Currency,
+    pub rate: Decimal,
+    #[serde(default = "default_rate_source")]
+    pub source: String,
+    /// Defaults to now
+    pub effective_at: Option<DateTime<Utc>>,
+}
+
+fn default_rate_source() -> String {
+    "manual".to_string()
+}
+
+/// Filter for rate history queries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExchangeRateFilter {
+    pub base_currency: Option<Currency>,
+    pub quote_currency: Option<Currency>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_to_base_rounds_to_cents() {
+        let rate = ExchangeRate {
+            id: Uuid::new_v4(),
+            base_currency: Currency::USD,
+            quote_currency: Currency::EUR,
+            rate: dec!(1.0857123456),
+            source: "manual".to_string(),
+            effective_at: Utc::now(),
+            created_at: Utc::now(),
+        };
+        assert_eq!(rate.to_base(dec!(100.00)), dec!(108.57));
+    }
+}
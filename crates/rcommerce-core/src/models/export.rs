@@ -0,0 +1,123 @@
+//! Data export models
+//!
+//! Products, customers and orders can be exported as CSV or JSON, filtered
+//! by creation date and status. Columns are fixed per entity so CSV files
+//! from different runs line up.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportEntity {
+    Products,
+    Customers,
+    Orders,
+}
+
+impl ExportEntity {
+    /// Columns in export order
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            ExportEntity::Products => &[
+                "id", "title", "slug", "sku", "product_type", "price", "compare_at_price", "cost_price",
+                "currency", "inventory_quantity", "inventory_policy", "is_active", "is_featured",
+                "requires_shipping", "weight", "weight_unit", "created_at", "updated_at",
+            ],
+            ExportEntity::Customers => &[
+                "id", "email", "first_name", "last_name", "phone", "accepts_marketing", "currency",
+                "is_verified", "order_count", "total_spent", "confirmed_at", "last_login_at", "created_at",
+            ],
+            ExportEntity::Orders => &[
+                "id", "order_number", "customer_id", "email", "status", "payment_status", "fulfillment_status",
+                "currency", "subtotal", "discount_total", "shipping_total", "tax_total", "total",
+                "gift_card_total", "gift_cards", "item_count", "coupon_code", "shipping_address",
+                "custom_fields", "notes", "created_at", "completed_at", "cancelled_at",
+            ],
+        }
+    }
+
+    /// Values accepted by the status filter
+    pub fn statuses(&self) -> &'static [&'static str] {
+        match self {
+            ExportEntity::Products => &["active", "inactive"],
+            ExportEntity::Customers => &["verified", "unverified"],
+            ExportEntity::Orders => &[
+                "pending", "confirmed", "processing", "on_hold", "completed", "cancelled", "refunded",
+            ],
+        }
+    }
+}
+
+impl std::fmt::Display for ExportEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportEntity::Products => "products",
+            ExportEntity::Customers => "customers",
+            ExportEntity::Orders => "orders",
+        })
+    }
+}
+
+impl std::str::FromStr for ExportEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "products" => Ok(ExportEntity::Products),
+            "customers" => Ok(ExportEntity::Customers),
+            "orders" => Ok(ExportEntity::Orders),
+            _ => Err(format!("Unknown export entity '{}' (products, customers, orders)", s)),
+        }
+    }
+}
+
+/// Export file format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// A JSON array of objects
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!("Unknown export format '{}' (csv, json)", s)),
+        }
+    }
+}
+
+/// Which records to export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportFilter {
+    /// Created at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Created before
+    pub until: Option<DateTime<Utc>>,
+    /// One of `ExportEntity::statuses`
+    pub status: Option<String>,
+}
@@ -0,0 +1,98 @@
+//! Flash sale models
+//!
+//! A flash sale caps how many units of a product (or one variant) can be
+//! sold in a short window. Customers enter a waiting room and are admitted
+//! with a purchase token, which is spent on the order at checkout. A sale
+//! moves draft -> live -> closed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Flash sale status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "flash_sale_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FlashSaleStatus {
+    /// Defined but not yet loaded into Redis
+    Draft,
+    /// Counters are in Redis; customers are admitted between starts_at and ends_at
+    Live,
+    Closed,
+}
+
+/// A flash sale
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FlashSale {
+    pub id: Uuid,
+    pub name: String,
+    pub product_id: Uuid,
+    /// None covers every variant of the product
+    pub variant_id: Option<Uuid>,
+    pub status: FlashSaleStatus,
+    pub stock_limit: i32,
+    /// Units one customer can buy (0 = no limit)
+    pub per_customer_limit: i32,
+    /// Purchase tokens held at once
+    pub max_active_tokens: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub sold_quantity: i32,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Live counters of a sale, read from Redis
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlashSaleStats {
+    pub remaining: i64,
+    pub sold: i64,
+    pub active_tokens: i64,
+    pub waiting: i64,
+}
+
+/// A flash sale with its live counters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashSaleDetails {
+    #[serde(flatten)]
+    pub sale: FlashSale,
+    /// None unless the sale is live
+    pub stats: Option<FlashSaleStats>,
+}
+
+/// Result of entering a sale's waiting room
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FlashSaleAdmission {
+    /// Send `token` with the checkout before it expires
+    Admitted { token: String, expires_at: DateTime<Utc> },
+    /// Ask again after `retry_after_secs`
+    Waiting { position: i64, retry_after_secs: u64 },
+    NotStarted { starts_at: DateTime<Utc> },
+    SoldOut,
+    /// The customer already bought as many as allowed
+    LimitReached,
+    Ended,
+}
+
+/// Request to define a flash sale
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateFlashSaleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    #[validate(range(min = 1))]
+    pub stock_limit: i32,
+    /// Default: `flash_sales.default_per_customer_limit`
+    #[validate(range(min = 0))]
+    pub per_customer_limit: Option<i32>,
+    /// Default: `flash_sales.default_max_active_tokens`
+    #[validate(range(min = 1))]
+    pub max_active_tokens: Option<i32>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
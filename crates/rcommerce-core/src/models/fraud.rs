@@ -0,0 +1,151 @@
+//! Fraud screening models
+//!
+//! Checkout screens each order with the configured risk scorers before it is
+//! paid. Their signals add up to the order's risk score (0-100), which picks
+//! the action: let it through, require 3-D Secure for the payment, hold the
+//! order for review, or cancel it. Held orders wait in the review queue until
+//! staff approve or reject them.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Highest risk score
+pub const MAX_RISK_SCORE: i32 = 100;
+
+/// What screening does with an order, in rising order of severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "risk_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RiskAction {
+    Allow,
+    /// Charge the payment with 3-D Secure
+    #[serde(rename = "require_3ds")]
+    #[sqlx(rename = "require_3ds")]
+    Require3ds,
+    /// Hold the order until staff review it
+    Review,
+    Cancel,
+}
+
+/// Where a held order's review stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "risk_review_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RiskReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// One reason an order looks risky
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskSignal {
+    /// Scorer that raised it, e.g. `velocity`
+    pub code: String,
+    /// Points added to the risk score
+    pub score: i32,
+    pub reason: String,
+}
+
+impl RiskSignal {
+    pub fn new(code: impl Into<String>, score: i32, reason: impl Into<String>) -> Self {
+        Self { code: code.into(), score, reason: reason.into() }
+    }
+}
+
+/// Risk score of signals: their points, capped at `MAX_RISK_SCORE`
+pub fn risk_score(signals: &[RiskSignal]) -> i32 {
+    signals.iter().map(|s| s.score.max(0)).sum::<i32>().min(MAX_RISK_SCORE)
+}
+
+/// What scorers know about an order being placed
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    pub order_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub email: String,
+    pub client_ip: Option<String>,
+    /// ISO country codes of the addresses
+    pub billing_country: Option<String>,
+    pub shipping_country: Option<String>,
+    pub total: Decimal,
+    pub currency: String,
+    pub history: RiskHistory,
+}
+
+/// Orders of the same buyer, counted before screening
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRow)]
+pub struct RiskHistory {
+    /// Earlier orders by the customer or email, cancelled ones excluded
+    pub previous_orders: i64,
+    /// Orders by the customer, email or IP address within the velocity
+    /// window, this one included
+    pub recent_orders: i64,
+}
+
+/// Screening result of an order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RiskAssessment {
+    pub order_id: Uuid,
+    pub score: i32,
+    pub action: RiskAction,
+    #[sqlx(json)]
+    pub signals: Vec<RiskSignal>,
+    pub email: String,
+    pub client_ip: Option<String>,
+    /// Set for orders held for review
+    pub review_status: Option<RiskReviewStatus>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A held order in the review queue
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RiskReviewItem {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub assessment: RiskAssessment,
+    pub order_number: String,
+    pub total: Decimal,
+    pub currency: String,
+    pub payment_status: String,
+}
+
+/// Approve or reject a held order
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RiskReviewRequest {
+    #[validate(length(max = 2000))]
+    pub note: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_score() {
+        assert_eq!(risk_score(&[]), 0);
+        let signals = vec![
+            RiskSignal::new("velocity", 40, "5 orders in 60 minutes"),
+            RiskSignal::new("country_mismatch", 25, "Billed in DE, shipped to NG"),
+        ];
+        assert_eq!(risk_score(&signals), 65);
+
+        let signals = vec![RiskSignal::new("a", 80, ""), RiskSignal::new("b", 70, ""), RiskSignal::new("c", -10, "")];
+        assert_eq!(risk_score(&signals), MAX_RISK_SCORE);
+    }
+
+    #[test]
+    fn test_risk_action_order_and_names() {
+        assert!(RiskAction::Allow < RiskAction::Require3ds);
+        assert!(RiskAction::Require3ds < RiskAction::Review);
+        assert!(RiskAction::Review < RiskAction::Cancel);
+        assert_eq!(serde_json::to_string(&RiskAction::Require3ds).unwrap(), "\"require_3ds\"");
+    }
+}
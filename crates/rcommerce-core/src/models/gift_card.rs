@@ -0,0 +1,257 @@
+//! Gift card models
+//!
+//! A gift card holds a balance in one currency that is spent at checkout,
+//! possibly over several orders. Cards are active until disabled by staff
+//! or expired; a card with no balance left stays active but cannot pay.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Characters used in codes (no 0/O or 1/I)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_GROUPS: usize = 4;
+const CODE_GROUP_LENGTH: usize = 4;
+
+/// Gift card status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "gift_card_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GiftCardStatus {
+    Active,
+    Disabled,
+    /// Past `expires_at`; the remaining balance was written off
+    Expired,
+}
+
+/// Kind of gift card balance change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "gift_card_transaction_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GiftCardTransactionKind {
+    Issue,
+    /// Spent on an order
+    Redeem,
+    /// A redemption given back because the checkout failed
+    Reverse,
+    /// Manual correction by staff
+    Adjust,
+    Expire,
+}
+
+/// A gift card
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GiftCard {
+    pub id: Uuid,
+    pub code: String,
+    pub currency: String,
+    pub initial_balance: Decimal,
+    pub balance: Decimal,
+    pub status: GiftCardStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub recipient_email: Option<String>,
+    pub recipient_name: Option<String>,
+    pub message: Option<String>,
+    pub note: Option<String>,
+    pub issued_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl GiftCard {
+    /// Generate a new code, e.g. "K7QM-2XWD-9HPA-R4TN"
+    pub fn generate_code() -> String {
+        let mut rng = rand::thread_rng();
+        (0..CODE_GROUPS)
+            .map(|_| {
+                (0..CODE_GROUP_LENGTH)
+                    .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Normalize a code as typed by a customer (case, spaces, dashes)
+    pub fn normalize_code(code: &str) -> String {
+        let chars: Vec<char> = code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        chars
+            .chunks(CODE_GROUP_LENGTH)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// The code with all but the last group hidden
+    pub fn masked_code(&self) -> String {
+        let last = self.code.rsplit('-').next().unwrap_or_default();
+        format!("****-****-****-{}", last)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status == GiftCardStatus::Expired || self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Why the card cannot pay right now, if it cannot
+    pub fn unusable_reason(&self, now: DateTime<Utc>) -> Option<&'static str> {
+        if self.is_expired(now) {
+            Some("has expired")
+        } else if self.status == GiftCardStatus::Disabled {
+            Some("is disabled")
+        } else if self.balance <= Decimal::ZERO {
+            Some("has no balance left")
+        } else {
+            None
+        }
+    }
+}
+
+/// A change to a gift card's balance
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GiftCardTransaction {
+    pub id: Uuid,
+    pub gift_card_id: Uuid,
+    pub kind: GiftCardTransactionKind,
+    /// Positive adds to the balance, negative spends it
+    pub amount: Decimal,
+    pub balance_after: Decimal,
+    pub order_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A gift card with its balance history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCardDetails {
+    #[serde(flatten)]
+    pub gift_card: GiftCard,
+    pub transactions: Vec<GiftCardTransaction>,
+}
+
+/// What a customer sees when checking a code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCardBalance {
+    pub code: String,
+    pub currency: String,
+    pub balance: Decimal,
+    pub status: GiftCardStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&GiftCard> for GiftCardBalance {
+    fn from(card: &GiftCard) -> Self {
+        Self {
+            code: card.masked_code(),
+            currency: card.currency.clone(),
+            balance: card.balance,
+            status: if card.is_expired(Utc::now()) { GiftCardStatus::Expired } else { card.status },
+            expires_at: card.expires_at,
+        }
+    }
+}
+
+/// Request to issue a gift card
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct IssueGiftCardRequest {
+    /// One of the configured denominations, or a custom amount if allowed
+    pub amount: Decimal,
+    /// Default: the store currency
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    /// Default: `gift_cards.expiry_days` from now
+    pub expires_at: Option<DateTime<Utc>>,
+    #[validate(email)]
+    pub recipient_email: Option<String>,
+    #[validate(length(max = 255))]
+    pub recipient_name: Option<String>,
+    #[validate(length(max = 1000))]
+    pub message: Option<String>,
+    pub note: Option<String>,
+}
+
+/// A new gift card, ready to insert
+#[derive(Debug, Clone)]
+pub struct NewGiftCard {
+    pub code: String,
+    pub currency: String,
+    pub amount: Decimal,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub recipient_email: Option<String>,
+    pub recipient_name: Option<String>,
+    pub message: Option<String>,
+    pub note: Option<String>,
+    pub issued_by: Option<Uuid>,
+}
+
+/// Manual balance correction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustGiftCardRequest {
+    /// Added to the balance; negative to deduct
+    pub amount: Decimal,
+    pub note: Option<String>,
+}
+
+/// Check a gift card code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GiftCardCodeRequest {
+    pub code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    fn card(balance: Decimal) -> GiftCard {
+        GiftCard {
+            id: Uuid::new_v4(),
+            code: GiftCard::generate_code(),
+            currency: "USD".to_string(),
+            initial_balance: dec!(50),
+            balance,
+            status: GiftCardStatus::Active,
+            expires_at: None,
+            recipient_email: None,
+            recipient_name: None,
+            message: None,
+            note: None,
+            issued_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_gift_card_codes() {
+        let code = GiftCard::generate_code();
+        assert_eq!(code.len(), 19);
+        assert_eq!(GiftCard::normalize_code(&code.to_lowercase().replace('-', " ")), code);
+        assert_eq!(GiftCard::normalize_code(" k7qm2xwd-9hpa r4tn "), "K7QM-2XWD-9HPA-R4TN");
+        assert!(card(dec!(5)).masked_code().starts_with("****-****-****-"));
+    }
+
+    #[test]
+    fn test_gift_card_usability() {
+        let now = Utc::now();
+        assert_eq!(card(dec!(10)).unusable_reason(now), None);
+        assert_eq!(card(dec!(0)).unusable_reason(now), Some("has no balance left"));
+
+        let mut expired = card(dec!(10));
+        expired.expires_at = Some(now - Duration::days(1));
+        assert_eq!(expired.unusable_reason(now), Some("has expired"));
+
+        let mut disabled = card(dec!(10));
+        disabled.status = GiftCardStatus::Disabled;
+        assert_eq!(disabled.unusable_reason(now), Some("is disabled"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Hosted checkout models
+//!
+//! A hosted checkout is a payment page at the gateway for one order: a
+//! checkout session the storefront redirects to, or a payment link staff
+//! send to the customer. See `crate::services::HostedCheckoutService`.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Kind of hosted payment page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "hosted_checkout_mode", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum HostedCheckoutMode {
+    /// Single-use page that expires (Stripe Checkout Session)
+    #[default]
+    CheckoutSession,
+    /// Shareable link that stays open until paid (Stripe Payment Link)
+    PaymentLink,
+}
+
+/// Hosted checkout status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "hosted_checkout_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum HostedCheckoutStatus {
+    Open,
+    /// Paid; the order was marked paid
+    Completed,
+    /// A delayed payment method (e.g. bank debit) failed
+    Failed,
+    Expired,
+}
+
+/// A payment page at the gateway for an order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct HostedCheckout {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub mode: HostedCheckoutMode,
+    /// Checkout session or payment link ID at the gateway
+    pub provider_id: String,
+    /// Where the customer pays
+    pub url: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: HostedCheckoutStatus,
+    pub success_url: String,
+    pub cancel_url: Option<String>,
+    /// Gateway payment that completed the checkout
+    pub payment_id: Option<String>,
+    pub error: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a hosted checkout for an order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateHostedCheckoutRequest {
+    #[serde(default)]
+    pub mode: HostedCheckoutMode,
+    /// Defaults to `payment.default_gateway`
+    pub gateway_id: Option<String>,
+    /// Where the customer returns after paying; defaults to
+    /// `payment.hosted_checkout.success_url`
+    pub success_url: Option<String>,
+    /// Where the customer returns when they cancel (checkout sessions only)
+    pub cancel_url: Option<String>,
+}
+
+/// A hosted checkout to record
+#[derive(Debug, Clone)]
+pub struct NewHostedCheckout {
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub mode: HostedCheckoutMode,
+    pub provider_id: String,
+    pub url: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub success_url: String,
+    pub cancel_url: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+}
@@ -0,0 +1,135 @@
+//! Idempotency key models
+//!
+//! A write request sent with an `Idempotency-Key` header is recorded with a
+//! fingerprint of the request before it runs, and its response is stored
+//! when it finishes. A retry with the same key replays that response; the
+//! same key on a different request is refused.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Longest accepted `Idempotency-Key`
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// Where a key stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "idempotency_key_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum IdempotencyKeyStatus {
+    /// The first request with the key is still running
+    InProgress,
+    /// The response is stored and will be replayed
+    Completed,
+}
+
+/// A recorded idempotency key
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyRecord {
+    pub scope: String,
+    pub idempotency_key: String,
+    pub method: String,
+    pub path: String,
+    pub request_hash: String,
+    pub status: IdempotencyKeyStatus,
+    pub lock_id: Uuid,
+    pub response_status: Option<i32>,
+    pub response_content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub locked_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl IdempotencyRecord {
+    /// The stored response, once the first request has finished
+    pub fn stored_response(&self) -> Option<StoredResponse> {
+        if self.status != IdempotencyKeyStatus::Completed {
+            return None;
+        }
+        Some(StoredResponse {
+            status: u16::try_from(self.response_status?).ok()?,
+            content_type: self.response_content_type.clone(),
+            body: self.response_body.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// A response kept for replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl StoredResponse {
+    /// Whether a response should be kept for replay
+    ///
+    /// Server errors, rate limiting and conflicts are not: the request may
+    /// well succeed when retried.
+    pub fn is_replayable(status: u16) -> bool {
+        status < 500 && status != 409 && status != 429
+    }
+}
+
+/// What to do with a request carrying an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyOutcome {
+    /// First request with the key: run it, then complete or release the key
+    Started { lock_id: Uuid },
+    /// A finished request with the same key and fingerprint
+    Replay(StoredResponse),
+    /// The first request with the key has not finished yet
+    InProgress,
+    /// The key was used for a different request
+    Mismatch,
+}
+
+/// SHA-256 hex fingerprint of a request's method, path and body
+pub fn request_fingerprint(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.to_ascii_uppercase().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Whether `key` is usable as an idempotency key: 1 to 255 visible ASCII characters
+pub fn is_valid_idempotency_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH
+        && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_fingerprint() {
+        let a = request_fingerprint("POST", "/orders", br#"{"total":"10"}"#);
+        assert_eq!(a, request_fingerprint("post", "/orders", br#"{"total":"10"}"#));
+        assert_ne!(a, request_fingerprint("POST", "/orders", br#"{"total":"11"}"#));
+        assert_ne!(a, request_fingerprint("POST", "/payments", br#"{"total":"10"}"#));
+        assert_eq!(a.len(), 64);
+    }
+
+    #[test]
+    fn test_idempotency_key_validation() {
+        assert!(is_valid_idempotency_key("3f1b6c1e-0f9a-4c43-9d55-6b1c0c7d2a10"));
+        assert!(!is_valid_idempotency_key(""));
+        assert!(!is_valid_idempotency_key("has space"));
+        assert!(!is_valid_idempotency_key(&"k".repeat(256)));
+        assert!(StoredResponse::is_replayable(201));
+        assert!(StoredResponse::is_replayable(422));
+        assert!(!StoredResponse::is_replayable(409));
+        assert!(!StoredResponse::is_replayable(503));
+    }
+}
@@ -0,0 +1,88 @@
+//! Incident models
+//!
+//! An incident is opened by the `anomaly_detection` job when a rate spikes
+//! far above its recent history, and stays unresolved (one per metric) until
+//! the rate is back to normal or staff resolve it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A rate watched for anomalies (all are percentages)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "anomaly_metric", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyMetric {
+    /// Failed / finished payments
+    PaymentFailureRate,
+    /// Refunds issued per order placed
+    RefundRate,
+    /// 5xx responses / API requests
+    ServerErrorRate,
+}
+
+impl AnomalyMetric {
+    pub fn all() -> Vec<AnomalyMetric> {
+        vec![
+            AnomalyMetric::PaymentFailureRate,
+            AnomalyMetric::RefundRate,
+            AnomalyMetric::ServerErrorRate,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyMetric::PaymentFailureRate => "payment_failure_rate",
+            AnomalyMetric::RefundRate => "refund_rate",
+            AnomalyMetric::ServerErrorRate => "server_error_rate",
+        }
+    }
+
+    /// Name used in notifications
+    pub fn title(&self) -> &'static str {
+        match self {
+            AnomalyMetric::PaymentFailureRate => "Payment failure rate",
+            AnomalyMetric::RefundRate => "Refund rate",
+            AnomalyMetric::ServerErrorRate => "5xx error rate",
+        }
+    }
+}
+
+/// Where an incident stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "incident_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentStatus {
+    Open,
+    /// Someone is looking into it
+    Acknowledged,
+    Resolved,
+}
+
+/// An anomaly in one of the watched rates
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Incident {
+    pub id: Uuid,
+    pub metric: AnomalyMetric,
+    pub status: IncidentStatus,
+    pub summary: String,
+    /// The latest rate, as a percentage
+    pub rate: f64,
+    /// Mean and standard deviation of the rate over the baseline windows
+    pub baseline_mean: f64,
+    pub baseline_stddev: f64,
+    pub z_score: f64,
+    /// Highest rate (and z-score) while the incident was unresolved
+    pub peak_rate: f64,
+    pub peak_z_score: f64,
+    /// Events the latest rate was measured from
+    pub samples: i64,
+    pub opened_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// None when the rate went back to normal by itself
+    pub resolved_by: Option<Uuid>,
+}
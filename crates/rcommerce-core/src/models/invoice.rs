@@ -0,0 +1,87 @@
+//! Customer billing document models
+//!
+//! Invoices are derived from orders rather than stored separately: every
+//! order gets an invoice numbered after its order number. Receipts are the
+//! gateway-hosted receipt pages recorded against successful payments.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{Currency, Order, OrderItem, PaymentStatus};
+
+/// Prefix used for invoice numbers
+pub const INVOICE_NUMBER_PREFIX: &str = "INV-";
+
+/// Invoice number for an order
+pub fn invoice_number(order_number: &str) -> String {
+    format!("{}{}", INVOICE_NUMBER_PREFIX, order_number)
+}
+
+/// Invoice list entry for a customer's order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct InvoiceSummary {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub currency: Currency,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub total: Decimal,
+    pub payment_status: PaymentStatus,
+    pub issued_at: DateTime<Utc>,
+    /// Number of payments with a gateway receipt
+    pub receipt_count: i64,
+}
+
+impl InvoiceSummary {
+    pub fn invoice_number(&self) -> String {
+        invoice_number(&self.order_number)
+    }
+}
+
+/// A recorded payment receipt
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentReceipt {
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: Currency,
+    pub status: PaymentStatus,
+    pub receipt_url: Option<String>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Everything needed to render an invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub invoice_number: String,
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+    pub receipts: Vec<PaymentReceipt>,
+}
+
+impl Invoice {
+    pub fn new(order: Order, items: Vec<OrderItem>, receipts: Vec<PaymentReceipt>) -> Self {
+        Self {
+            invoice_number: invoice_number(&order.order_number),
+            order,
+            items,
+            receipts,
+        }
+    }
+}
+
+/// Successful payment to record against an order
+#[derive(Debug, Clone)]
+pub struct RecordPaymentReceipt {
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub receipt_url: Option<String>,
+}
@@ -0,0 +1,64 @@
+//! Login session models
+//!
+//! A login session is one signed-in device. It holds the hash of its
+//! current refresh token and of the one it replaced, so a rotated token
+//! presented again is recognised as stolen.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Prefix marking refresh tokens
+pub const REFRESH_TOKEN_PREFIX: &str = "rt_";
+
+/// A login session; the token hashes are never serialized
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LoginSession {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    #[serde(skip)]
+    pub refresh_token_hash: String,
+    #[serde(skip)]
+    pub previous_token_hash: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>,
+}
+
+impl LoginSession {
+    /// Not revoked and not expired
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// The device a login came from, shown when listing sessions
+#[derive(Debug, Clone, Default)]
+pub struct LoginDevice {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// SHA-256 of a refresh token, as stored
+pub fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_refresh_token() {
+        let hash = hash_refresh_token("rt_abc");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_refresh_token("rt_abc"));
+        assert_ne!(hash, hash_refresh_token("rt_abd"));
+    }
+}
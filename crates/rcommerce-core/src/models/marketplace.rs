@@ -0,0 +1,265 @@
+//! Marketplace models
+//!
+//! Listings map marketplace SKUs to products and variants; imported orders
+//! record which marketplace order each store order came from. See the
+//! `marketplace` module for the sync itself.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// External marketplace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "marketplace_channel", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MarketplaceChannel {
+    Amazon,
+    Ebay,
+}
+
+impl MarketplaceChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketplaceChannel::Amazon => "amazon",
+            MarketplaceChannel::Ebay => "ebay",
+        }
+    }
+
+    /// Prefix of the order numbers of imported orders
+    pub fn order_prefix(&self) -> &'static str {
+        match self {
+            MarketplaceChannel::Amazon => "AMZ",
+            MarketplaceChannel::Ebay => "EBAY",
+        }
+    }
+}
+
+impl fmt::Display for MarketplaceChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MarketplaceChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "amazon" => Ok(MarketplaceChannel::Amazon),
+            "ebay" => Ok(MarketplaceChannel::Ebay),
+            _ => Err(format!("Unknown marketplace: {}", s)),
+        }
+    }
+}
+
+/// A product or variant listed on a marketplace under a marketplace SKU
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceListing {
+    pub id: Uuid,
+    pub channel: MarketplaceChannel,
+    pub marketplace_sku: String,
+    pub product_id: Uuid,
+    /// None lists the product itself
+    pub variant_id: Option<Uuid>,
+    /// Percentage added to (or, negative, taken off) the store price
+    pub price_adjustment_percent: Decimal,
+    /// Units held back from the marketplace
+    pub stock_buffer: i32,
+    pub sync_price: bool,
+    pub sync_inventory: bool,
+    pub active: bool,
+    /// Last change to the listing or to the price or stock it mirrors
+    pub changed_at: DateTime<Utc>,
+    pub synced_at: Option<DateTime<Utc>>,
+    pub last_synced_price: Option<Decimal>,
+    pub last_synced_quantity: Option<i32>,
+    /// Error of the last push, cleared by a successful one
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MarketplaceListing {
+    /// Whether the listing changed since it was last pushed
+    pub fn is_pending(&self) -> bool {
+        self.active && self.synced_at.map_or(true, |synced| self.changed_at > synced)
+    }
+
+    /// Price to list at for a store price, rounded to cents
+    pub fn marketplace_price(&self, price: Decimal) -> Decimal {
+        (price * (Decimal::ONE_HUNDRED + self.price_adjustment_percent) / Decimal::ONE_HUNDRED).round_dp(2)
+    }
+
+    /// Quantity to offer for the store's stock
+    pub fn marketplace_quantity(&self, stock: i32) -> i32 {
+        (stock - self.stock_buffer).max(0)
+    }
+}
+
+/// A listing due for a push, with the current store price and stock of
+/// what it lists
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PendingListing {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub listing: MarketplaceListing,
+    pub price: Decimal,
+    pub currency: super::Currency,
+    pub inventory_quantity: i32,
+}
+
+/// Request to list a product or variant on a marketplace
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateMarketplaceListingRequest {
+    pub channel: MarketplaceChannel,
+    #[validate(length(min = 1, max = 255))]
+    pub marketplace_sku: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    #[validate(custom = "validate_price_adjustment")]
+    #[serde(default)]
+    pub price_adjustment_percent: Decimal,
+    #[validate(range(min = 0))]
+    #[serde(default)]
+    pub stock_buffer: i32,
+    #[serde(default = "default_true")]
+    pub sync_price: bool,
+    #[serde(default = "default_true")]
+    pub sync_inventory: bool,
+}
+
+/// Request to change a listing; omitted fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateMarketplaceListingRequest {
+    #[validate(custom = "validate_price_adjustment")]
+    pub price_adjustment_percent: Option<Decimal>,
+    #[validate(range(min = 0))]
+    pub stock_buffer: Option<i32>,
+    pub sync_price: Option<bool>,
+    pub sync_inventory: Option<bool>,
+    pub active: Option<bool>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn validate_price_adjustment(percent: &Decimal) -> std::result::Result<(), validator::ValidationError> {
+    if *percent <= -Decimal::ONE_HUNDRED || *percent > Decimal::from(1000) {
+        return Err(validator::ValidationError::new("price_adjustment_out_of_range"));
+    }
+    Ok(())
+}
+
+/// A marketplace order imported as a store order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceOrder {
+    pub id: Uuid,
+    pub channel: MarketplaceChannel,
+    pub marketplace_order_id: String,
+    pub order_id: Uuid,
+    /// SKUs that matched no listing; the order was put on hold
+    pub unmapped_skus: Vec<String>,
+    pub placed_at: DateTime<Utc>,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// A marketplace order to import, with its lines matched to listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceOrderImport {
+    pub channel: MarketplaceChannel,
+    pub marketplace_order_id: String,
+    pub order_number: String,
+    pub placed_at: DateTime<Utc>,
+    pub email: String,
+    pub currency: super::Currency,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub shipping_total: Decimal,
+    pub total: Decimal,
+    pub shipping_address: Option<serde_json::Value>,
+    pub notes: Option<String>,
+    pub lines: Vec<MarketplaceOrderImportLine>,
+    /// Marketplace SKUs without a listing; their lines are left out and
+    /// the order is put on hold
+    pub unmapped_skus: Vec<String>,
+}
+
+/// A line of an imported order, matched to a listed product or variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceOrderImportLine {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub sku: String,
+    pub title: String,
+    pub quantity: i32,
+    /// Unit price
+    pub price: Decimal,
+    pub tax_amount: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn listing(adjustment: Decimal, buffer: i32) -> MarketplaceListing {
+        let now = Utc::now();
+        MarketplaceListing {
+            id: Uuid::new_v4(),
+            channel: MarketplaceChannel::Amazon,
+            marketplace_sku: "TEE-M".to_string(),
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            price_adjustment_percent: adjustment,
+            stock_buffer: buffer,
+            sync_price: true,
+            sync_inventory: true,
+            active: true,
+            changed_at: now,
+            synced_at: None,
+            last_synced_price: None,
+            last_synced_quantity: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_marketplace_price_and_quantity() {
+        let listing = listing(dec!(15), 2);
+        assert_eq!(listing.marketplace_price(dec!(19.99)), dec!(22.99));
+        assert_eq!(listing.marketplace_quantity(10), 8);
+        assert_eq!(listing.marketplace_quantity(1), 0);
+
+        let discounted = self::listing(dec!(-10), 0);
+        assert_eq!(discounted.marketplace_price(dec!(20.00)), dec!(18.00));
+    }
+
+    #[test]
+    fn test_is_pending() {
+        let mut listing = listing(Decimal::ZERO, 0);
+        assert!(listing.is_pending());
+        listing.synced_at = Some(listing.changed_at);
+        assert!(!listing.is_pending());
+        listing.changed_at += chrono::Duration::seconds(1);
+        assert!(listing.is_pending());
+        listing.active = false;
+        assert!(!listing.is_pending());
+    }
+
+    #[test]
+    fn test_channel_round_trip() {
+        for channel in [MarketplaceChannel::Amazon, MarketplaceChannel::Ebay] {
+            assert_eq!(channel.as_str().parse::<MarketplaceChannel>(), Ok(channel));
+        }
+        assert!("etsy".parse::<MarketplaceChannel>().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+//! Product media models
+//!
+//! A product image ([`super::ProductImage`]) is stored once as uploaded
+//! and once per configured image size (`media.image_processing.sizes`).
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// A resized copy of a product image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ImageVariant {
+    /// Image size name, e.g. `thumbnail`
+    pub name: String,
+    pub src: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Where the file is kept in media storage
+    #[serde(default)]
+    pub storage_key: String,
+}
+
+/// Request to add a product image from a URL; the image is downloaded
+/// into media storage
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AddProductImageRequest {
+    #[validate(url)]
+    pub url: String,
+    #[validate(length(max = 255))]
+    pub alt_text: Option<String>,
+    /// Show the image for one variant only
+    pub variant_id: Option<Uuid>,
+}
+
+/// Request to update a product image
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateProductImageRequest {
+    #[validate(length(max = 255))]
+    pub alt_text: Option<String>,
+    #[serde(default, deserialize_with = "super::category::double_option")]
+    pub variant_id: Option<Option<Uuid>>,
+}
+
+/// Request to put a product's images in order; images left out follow
+/// in their current order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderProductImagesRequest {
+    pub image_ids: Vec<Uuid>,
+}
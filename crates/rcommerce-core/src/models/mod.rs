@@ -6,19 +6,109 @@ use validator::Validate;
 pub mod customer;
 pub mod order;
 pub mod product;
+pub mod attribute;
 pub mod address;
 pub mod subscription;
 pub mod cart;
 pub mod coupon;
+pub mod exchange_rate;
+pub mod invoice;
+pub mod price_history;
+pub mod order_archive;
+pub mod webhook_replay;
+pub mod register;
+pub mod price_list;
+pub mod addon;
+pub mod returns;
+pub mod role;
+pub mod checkout_field;
+pub mod gift_card;
+pub mod flash_sale;
+pub mod export;
+pub mod purchase_limit;
+pub mod idempotency;
+pub mod access_denial;
+pub mod access_log;
+pub mod scheduled_job;
+pub mod marketplace;
+pub mod automation;
+pub mod report;
+pub mod hosted_checkout;
+pub mod catalog_promotion;
+pub mod config_bundle;
+pub mod redirect;
+pub mod customer_group;
+pub mod incident;
+pub mod fraud;
+pub mod payment_session;
+pub mod payment_capture;
+pub mod payout;
+pub mod category;
+pub mod collection;
+pub mod media;
+pub mod customs;
+pub mod print_batch;
+pub mod login_session;
+pub mod two_factor;
+pub mod oauth;
+pub mod audit;
+pub mod soft_delete;
+pub mod account;
+pub mod cursor;
 
 // Re-export common models
 pub use customer::*;
 pub use order::*;
 pub use product::*;
+pub use attribute::*;
 pub use address::*;
 pub use subscription::*;
 pub use cart::*;
 pub use coupon::*;
+pub use exchange_rate::*;
+pub use invoice::*;
+pub use price_history::*;
+pub use order_archive::*;
+pub use webhook_replay::*;
+pub use register::*;
+pub use price_list::*;
+pub use addon::*;
+pub use returns::*;
+pub use role::*;
+pub use checkout_field::*;
+pub use gift_card::*;
+pub use flash_sale::*;
+pub use export::*;
+pub use purchase_limit::*;
+pub use idempotency::*;
+pub use access_denial::*;
+pub use access_log::*;
+pub use scheduled_job::*;
+pub use marketplace::*;
+pub use automation::*;
+pub use report::*;
+pub use hosted_checkout::*;
+pub use catalog_promotion::*;
+pub use config_bundle::*;
+pub use redirect::*;
+pub use customer_group::*;
+pub use incident::*;
+pub use fraud::*;
+pub use payment_session::*;
+pub use payment_capture::*;
+pub use payout::*;
+pub use category::*;
+pub use collection::*;
+pub use media::*;
+pub use customs::*;
+pub use print_batch::*;
+pub use login_session::*;
+pub use two_factor::*;
+pub use oauth::*;
+pub use audit::*;
+pub use soft_delete::*;
+pub use account::*;
+pub use cursor::{after_cursor_sql, Cursor, CursorPage};
 
 /// Common trait for all entities
 pub trait Entity: Send + Sync {
@@ -67,7 +157,7 @@ pub struct SortParams {
 }
 
 /// Currency representation
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type, Default, utoipa::ToSchema)]
 #[sqlx(type_name = "currency", rename_all = "UPPERCASE")]
 pub enum Currency {
     #[default]
@@ -118,7 +208,7 @@ impl std::str::FromStr for Currency {
 }
 
 /// Weight units
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
 #[sqlx(type_name = "weight_unit", rename_all = "snake_case")]
 pub enum WeightUnit {
     G,
@@ -138,7 +228,7 @@ pub enum LengthUnit {
 }
 
 /// Inventory policy
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, Default, utoipa::ToSchema)]
 #[sqlx(type_name = "inventory_policy", rename_all = "snake_case")]
 pub enum InventoryPolicy {
     #[default]
@@ -159,17 +249,36 @@ pub struct ProductImage {
     pub height: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Where the file is kept in media storage; None for images linked
+    /// from elsewhere
+    #[sqlx(default)]
+    #[serde(skip_serializing)]
+    pub storage_key: Option<String>,
+    #[sqlx(default)]
+    pub content_type: Option<String>,
+    #[sqlx(default)]
+    pub file_size: Option<i64>,
+    /// Resized copies, one per configured image size
+    #[sqlx(default)]
+    #[serde(default)]
+    pub variants: sqlx::types::Json<Vec<ImageVariant>>,
+    /// Where an imported image was downloaded from
+    #[sqlx(default)]
+    pub source_url: Option<String>,
 }
 
-/// Product category
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+/// Product category; categories form a tree through `parent_id`
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct ProductCategory {
     pub id: Uuid,
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
     pub parent_id: Option<Uuid>,
+    pub image_url: Option<String>,
     pub sort_order: i32,
+    /// Inactive categories are hidden from the storefront
+    pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
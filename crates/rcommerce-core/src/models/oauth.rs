@@ -0,0 +1,147 @@
+//! OAuth / OpenID Connect login models
+//!
+//! Accounts at external identity providers linked to customers, and the
+//! logins in flight between the redirect to the provider and the storefront
+//! exchanging its one-time login code.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Prefix marking one-time OAuth login codes
+pub const OAUTH_LOGIN_CODE_PREFIX: &str = "oac_";
+
+/// A supported identity provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthProvider {
+    Google,
+    Apple,
+    Github,
+}
+
+impl OAuthProvider {
+    pub const ALL: [OAuthProvider; 3] = [OAuthProvider::Google, OAuthProvider::Apple, OAuthProvider::Github];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "google",
+            OAuthProvider::Apple => "apple",
+            OAuthProvider::Github => "github",
+        }
+    }
+}
+
+impl std::fmt::Display for OAuthProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for OAuthProvider {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        OAuthProvider::ALL
+            .into_iter()
+            .find(|provider| provider.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| crate::Error::not_found(format!("Unknown login provider '{}'", s)))
+    }
+}
+
+/// A provider account linked to a customer
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct OAuthIdentity {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub provider: String,
+    /// The provider's account ID
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: DateTime<Utc>,
+}
+
+/// A login between the redirect to the provider and the code exchange
+#[derive(Debug, Clone, FromRow)]
+pub struct OAuthLogin {
+    pub id: Uuid,
+    pub provider: String,
+    pub state_hash: String,
+    /// PKCE verifier sent with the authorization code
+    pub code_verifier: String,
+    pub nonce: String,
+    pub return_to: String,
+    pub client_state: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub callback_at: Option<DateTime<Utc>>,
+    pub customer_id: Option<Uuid>,
+    pub created_customer: bool,
+    pub login_code_hash: Option<String>,
+    pub login_code_expires_at: Option<DateTime<Utc>>,
+    pub consumed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A login to record when redirecting to the provider
+#[derive(Debug, Clone)]
+pub struct NewOAuthLogin {
+    pub provider: OAuthProvider,
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub return_to: String,
+    pub client_state: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The account a provider vouched for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalProfile {
+    pub provider: OAuthProvider,
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider verified the email belongs to the account
+    pub email_verified: bool,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+impl ExternalProfile {
+    /// The email, if the provider verified it
+    pub fn verified_email(&self) -> Option<&str> {
+        self.email.as_deref().filter(|_| self.email_verified)
+    }
+}
+
+/// What a provider sends to the callback, in the query or (Apple) a form
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OAuthCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set when the customer declined, e.g. `access_denied`
+    pub error: Option<String>,
+    /// Apple: JSON with the customer's name, sent on the first login only
+    pub user: Option<String>,
+}
+
+/// SHA-256 of a state or login code, as stored
+pub fn hash_oauth_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_round_trip() {
+        for provider in OAuthProvider::ALL {
+            assert_eq!(provider.as_str().parse::<OAuthProvider>().unwrap(), provider);
+        }
+        assert_eq!("GitHub".parse::<OAuthProvider>().unwrap(), OAuthProvider::Github);
+        assert!("facebook".parse::<OAuthProvider>().is_err());
+    }
+}
@@ -74,6 +74,10 @@ pub enum FulfillmentStatus {
     Delivered,
     Cancelled,
     Returned,
+    /// Waiting at its pickup location for the customer
+    ReadyForPickup,
+    /// Collected by the customer at its pickup location
+    PickedUp,
 }
 
 /// Payment status
@@ -0,0 +1,52 @@
+//! Order archive models
+//!
+//! Finished orders older than the configured threshold are moved out of the
+//! hot `orders` table into `orders_archive`, keeping a snapshot of the order
+//! and its dependent rows.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{Currency, OrderStatus};
+
+/// An archived order
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ArchivedOrder {
+    pub id: Uuid,
+    pub order_number: String,
+    pub customer_id: Option<Uuid>,
+    pub email: String,
+    pub status: OrderStatus,
+    pub currency: Currency,
+    pub total: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub archived_at: DateTime<Utc>,
+    /// Rows as of archival, keyed by source table ("order", "order_items", ...)
+    pub data: serde_json::Value,
+}
+
+/// Archive size overview
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrderArchiveStats {
+    pub archived_orders: i64,
+    pub oldest_order_at: Option<DateTime<Utc>>,
+    pub last_archived_at: Option<DateTime<Utc>>,
+}
+
+/// Result of an archival run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderArchiveReport {
+    /// Orders moved to the archive
+    pub archived: u64,
+    /// Orders eligible but not moved (dry run)
+    pub eligible: u64,
+    pub cutoff: Option<DateTime<Utc>>,
+    /// JSON Lines file the archived orders were exported to
+    pub export_file: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
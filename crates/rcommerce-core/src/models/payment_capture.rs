@@ -0,0 +1,139 @@
+//! Payment capture models
+//!
+//! A payment authorized with `payment.capture.method = "manual"` holds its
+//! `authorized_amount` until it is captured, in one or several captures
+//! (one per shipment with capture-on-fulfillment), or voided. See
+//! `crate::services::PaymentCaptureService`.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// What captured a payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "capture_source", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    /// Captured by staff
+    Manual,
+    /// Captured as a shipment shipped
+    Fulfillment,
+}
+
+/// An authorized payment and how much of it was captured
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentAuthorization {
+    /// Payment ID
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub currency: String,
+    /// `authorized`, `paid` once fully captured, `cancelled` once voided
+    pub status: String,
+    pub authorized_amount: Decimal,
+    pub captured_amount: Decimal,
+    pub authorization_expires_at: Option<DateTime<Utc>>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PaymentAuthorization {
+    /// Amount still to capture
+    pub fn remaining(&self) -> Decimal {
+        (self.authorized_amount - self.captured_amount).max(Decimal::ZERO)
+    }
+
+    /// Whether more can still be captured
+    pub fn is_open(&self) -> bool {
+        self.status == "authorized"
+    }
+
+    /// Whether the authorization has lapsed
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.authorization_expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// A capture of an authorized payment
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentCapture {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    pub amount: Decimal,
+    pub currency: String,
+    pub gateway_capture_id: Option<String>,
+    /// The rest of the authorization was released
+    pub final_capture: bool,
+    pub source: CaptureSource,
+    pub shipment_id: Option<Uuid>,
+    pub captured_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A capture to record
+#[derive(Debug, Clone)]
+pub struct NewPaymentCapture {
+    pub amount: Decimal,
+    pub gateway_capture_id: Option<String>,
+    pub final_capture: bool,
+    pub source: CaptureSource,
+    pub shipment_id: Option<Uuid>,
+    pub captured_by: Option<Uuid>,
+}
+
+/// Request to capture an authorized payment
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CapturePaymentRequest {
+    /// Defaults to what is left of the authorization
+    pub amount: Option<Decimal>,
+    /// Release the rest of the authorization; implied when capturing all of it
+    #[serde(default)]
+    pub final_capture: bool,
+}
+
+/// What a shipment means for its order's authorization
+#[derive(Debug, Clone, FromRow)]
+pub struct ShipmentCapture {
+    pub shipment_id: Uuid,
+    pub order_id: Uuid,
+    /// Price of the shipped items
+    pub value: Decimal,
+    /// Order units not shipped yet, this shipment counted as shipped
+    pub unshipped_units: i64,
+    /// The order's own capture-on-fulfillment setting
+    pub capture_on_fulfillment: Option<bool>,
+    /// A capture was already made for this shipment
+    pub captured: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_authorization_remaining() {
+        let now = Utc::now();
+        let authorization = PaymentAuthorization {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            gateway: "stripe".to_string(),
+            gateway_payment_id: Some("pi_1".to_string()),
+            currency: "EUR".to_string(),
+            status: "authorized".to_string(),
+            authorized_amount: dec!(100.00),
+            captured_amount: dec!(40.00),
+            authorization_expires_at: Some(now + chrono::Duration::days(7)),
+            voided_at: None,
+            created_at: now,
+        };
+        assert_eq!(authorization.remaining(), dec!(60.00));
+        assert!(authorization.is_open());
+        assert!(!authorization.is_expired(now));
+        assert!(authorization.is_expired(now + chrono::Duration::days(8)));
+    }
+}
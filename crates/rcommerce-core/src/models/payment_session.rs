@@ -0,0 +1,189 @@
+//! Payment session models
+//!
+//! A payment session follows one payment started through the payments API
+//! until it settles: which gateway took it, the action it waits for (a
+//! 3-D Secure challenge) and where the customer goes once that is done.
+//! See `crate::services::PaymentSessionService`.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Payment session status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "payment_session_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentSessionStatus {
+    /// Not yet answered by the gateway
+    Pending,
+    /// Waiting for the customer, e.g. a 3-D Secure challenge
+    RequiresAction,
+    Processing,
+    /// Funds held until captured (`payment.capture.method = "manual"`)
+    Authorized,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The action wasn't completed in time
+    Expired,
+}
+
+impl PaymentSessionStatus {
+    /// Whether the session is settled and won't change again; captures of
+    /// an authorized payment are followed on the payment
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Authorized | Self::Succeeded | Self::Failed | Self::Cancelled | Self::Expired)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::RequiresAction => "requires_action",
+            Self::Processing => "processing",
+            Self::Authorized => "authorized",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Expired => "expired",
+        }
+    }
+}
+
+/// A payment followed until it settles
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentSession {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: PaymentSessionStatus,
+    /// Pending action (`three_d_secure`, `redirect`, ...)
+    pub action_type: Option<String>,
+    /// Its data; a `ThreeDsChallenge` for `three_d_secure`
+    pub action_data: Option<serde_json::Value>,
+    /// Storefront page the customer returns to after a challenge
+    pub return_url: Option<String>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub receipt_url: Option<String>,
+    /// When the pending action lapses
+    pub expires_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PaymentSession {
+    /// Whether the pending action has lapsed
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status == PaymentSessionStatus::RequiresAction && self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// The storefront return URL with `payment_session` and `status` added
+    pub fn return_redirect(&self) -> Option<String> {
+        let mut url = url::Url::parse(self.return_url.as_deref()?).ok()?;
+        url.query_pairs_mut()
+            .append_pair("payment_session", &self.id.to_string())
+            .append_pair("status", self.status.as_str());
+        Some(url.into())
+    }
+}
+
+/// A payment session to record
+#[derive(Debug, Clone)]
+pub struct NewPaymentSession {
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub return_url: Option<String>,
+}
+
+/// What the gateway said about a session's payment
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentSessionUpdate {
+    pub gateway_payment_id: Option<String>,
+    pub status: PaymentSessionStatus,
+    pub action_type: Option<String>,
+    pub action_data: Option<serde_json::Value>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub receipt_url: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When an authorized payment must be captured by
+    pub authorization_expires_at: Option<DateTime<Utc>>,
+}
+
+impl PaymentSessionUpdate {
+    /// Just a new status
+    pub fn status(status: PaymentSessionStatus) -> Self {
+        Self {
+            gateway_payment_id: None,
+            status,
+            action_type: None,
+            action_data: None,
+            error_code: None,
+            error_message: None,
+            receipt_url: None,
+            expires_at: None,
+            authorization_expires_at: None,
+        }
+    }
+
+    /// A failed payment
+    pub fn failed(error_code: impl Into<String>, error_message: impl Into<String>) -> Self {
+        Self {
+            error_code: Some(error_code.into()),
+            error_message: Some(error_message.into()),
+            ..Self::status(PaymentSessionStatus::Failed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_expiry() {
+        let now = Utc::now();
+        let mut session = PaymentSession {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            gateway: "stripe".to_string(),
+            gateway_payment_id: Some("pi_1".to_string()),
+            amount: Decimal::from(25),
+            currency: "EUR".to_string(),
+            status: PaymentSessionStatus::RequiresAction,
+            action_type: Some("three_d_secure".to_string()),
+            action_data: None,
+            return_url: None,
+            error_code: None,
+            error_message: None,
+            receipt_url: None,
+            expires_at: Some(now - chrono::Duration::minutes(1)),
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert!(session.is_expired(now));
+        assert_eq!(session.return_redirect(), None);
+
+        session.status = PaymentSessionStatus::Succeeded;
+        assert!(!session.is_expired(now));
+        session.return_url = Some("https://shop.example.com/checkout/done?step=3".to_string());
+        assert_eq!(
+            session.return_redirect(),
+            Some(format!(
+                "https://shop.example.com/checkout/done?step=3&payment_session={}&status=succeeded",
+                session.id
+            ))
+        );
+        assert!(session.status.is_final());
+        assert!(!PaymentSessionStatus::RequiresAction.is_final());
+    }
+}
@@ -0,0 +1,155 @@
+//! Payout reconciliation models
+//!
+//! Gateway payouts and the balance transactions each paid out, matched
+//! against recorded payments and refunds. See
+//! `crate::services::PayoutReconciliationService`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::payment::agnostic::{SettlementKind, SettlementTransaction};
+
+/// How a paid out transaction compares to what was recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "reconciliation_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    /// A recorded payment or refund of the same amount
+    Matched,
+    /// A recorded payment or refund of another amount
+    AmountMismatch,
+    /// Nothing recorded under its reference
+    Unmatched,
+    /// A gateway fee
+    Fee,
+    /// Disputes, reserves and other adjustments
+    Adjustment,
+}
+
+impl ReconciliationStatus {
+    /// Whether finance should look at it
+    pub fn is_flagged(&self) -> bool {
+        matches!(self, Self::AmountMismatch | Self::Unmatched)
+    }
+}
+
+/// An imported payout
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Payout {
+    pub id: Uuid,
+    pub gateway: String,
+    pub gateway_payout_id: String,
+    /// Net amount paid out
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    /// Charges less refunds
+    pub gross: Decimal,
+    pub fees: Decimal,
+    pub arrival_date: Option<DateTime<Utc>>,
+    pub gateway_created_at: DateTime<Utc>,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// A transaction paid out in a payout
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PayoutLine {
+    pub id: Uuid,
+    pub payout_id: Uuid,
+    pub gateway_transaction_id: String,
+    pub kind: SettlementKind,
+    pub reference: Option<String>,
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub net: Decimal,
+    pub currency: String,
+    pub description: Option<String>,
+    pub payment_id: Option<Uuid>,
+    pub refund_id: Option<Uuid>,
+    pub expected_amount: Option<Decimal>,
+    pub expected_currency: Option<String>,
+    pub status: ReconciliationStatus,
+}
+
+/// A recorded payment or refund a transaction settles
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct RecordedTransaction {
+    pub id: Uuid,
+    /// Captured amount of a payment, or the refunded amount
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+/// A paid out transaction with what it matched
+#[derive(Debug, Clone)]
+pub struct ReconciledLine {
+    pub transaction: SettlementTransaction,
+    pub recorded: Option<RecordedTransaction>,
+    pub status: ReconciliationStatus,
+}
+
+/// A payout with its lines
+#[derive(Debug, Clone, Serialize)]
+pub struct PayoutDetail {
+    #[serde(flatten)]
+    pub payout: Payout,
+    pub lines: Vec<PayoutLine>,
+}
+
+/// What an import run found
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PayoutImport {
+    pub payouts: usize,
+    pub lines: usize,
+    pub flagged: usize,
+    /// Gateways whose payouts could not be listed
+    pub failed_gateways: Vec<String>,
+}
+
+/// Payout totals in one currency
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PayoutTotals {
+    pub currency: String,
+    pub payouts: i64,
+    pub gross: Decimal,
+    pub fees: Decimal,
+    pub net: Decimal,
+}
+
+/// Lines of one reconciliation status
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ReconciliationCount {
+    pub status: ReconciliationStatus,
+    pub lines: i64,
+    pub amount: Decimal,
+}
+
+/// A payment not paid out in time
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UnsettledPayment {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Reconciliation of the payouts arriving in a range of days
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub gateway: Option<String>,
+    pub totals: Vec<PayoutTotals>,
+    pub lines: Vec<ReconciliationCount>,
+    /// Mismatched and unmatched lines
+    pub flagged: Vec<PayoutLine>,
+    /// Payments taken in the range and not paid out after
+    /// `settlement_days`
+    pub unsettled: Vec<UnsettledPayment>,
+}
@@ -0,0 +1,58 @@
+//! Price history models (EU Omnibus Directive)
+//!
+//! Every product and variant price change is recorded so that a price
+//! reduction can be shown next to the lowest price of the prior 30 days,
+//! and so the history can be produced during audits.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::Currency;
+
+/// Days before a price reduction considered for the prior price
+pub const PRIOR_PRICE_WINDOW_DAYS: i32 = 30;
+
+/// A recorded price
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PriceHistoryEntry {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    /// None for the product's own price
+    pub variant_id: Option<Uuid>,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub currency: Currency,
+    /// "created", "updated" or "baseline"
+    pub source: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Filter for price history queries
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PriceHistoryFilter {
+    pub variant_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Whether a price is shown as a reduction from its compare-at price
+pub fn is_on_sale(price: Decimal, compare_at_price: Option<Decimal>) -> bool {
+    compare_at_price.is_some_and(|compare_at| compare_at > price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_is_on_sale() {
+        assert!(is_on_sale(dec!(79.99), Some(dec!(99.99))));
+        assert!(!is_on_sale(dec!(99.99), Some(dec!(99.99))));
+        assert!(!is_on_sale(dec!(99.99), Some(dec!(79.99))));
+        assert!(!is_on_sale(dec!(99.99), None));
+    }
+}
@@ -0,0 +1,156 @@
+//! Price list models
+//!
+//! A product's own price and currency are its base price. Price lists carry
+//! prices in other currencies, optionally limited to some countries; when no
+//! list prices a product in the shopper's currency the base price is
+//! converted at the current FX rate.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::Currency;
+
+/// A per-currency (and optionally per-region) price list
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PriceList {
+    pub id: Uuid,
+    pub name: String,
+    pub currency: Currency,
+    /// ISO country codes the list applies to; empty applies everywhere
+    pub countries: Vec<String>,
+    /// Higher priority wins when several lists match
+    pub priority: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PriceList {
+    /// Whether the list applies to shoppers in `country` (None = unknown)
+    pub fn applies_to(&self, country: Option<&str>) -> bool {
+        self.countries.is_empty()
+            || country.is_some_and(|c| self.countries.iter().any(|listed| listed.eq_ignore_ascii_case(c)))
+    }
+}
+
+/// A product or variant price on a list
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PriceListPrice {
+    pub id: Uuid,
+    pub price_list_id: Uuid,
+    pub product_id: Uuid,
+    /// None prices the product and all of its variants
+    pub variant_id: Option<Uuid>,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a price list
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePriceListRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub currency: Currency,
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Request to update a price list
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePriceListRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub countries: Option<Vec<String>>,
+    pub priority: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// Request to set a product or variant price on a list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetPriceListPriceRequest {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+}
+
+/// Where a resolved price came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PriceSource {
+    /// The product's own price, already in the requested currency
+    Base,
+    /// A price list price
+    PriceList { price_list_id: Uuid },
+    /// The base price converted at an FX rate
+    Converted { from: Currency, rate: Decimal, provider: String },
+    /// The customer's group price, or the regular price less the group's
+    /// discount (a higher regular price becomes the compare-at price)
+    CustomerGroup { customer_group_id: Uuid },
+}
+
+/// A product price in a shopper's currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedPrice {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub currency: Currency,
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub source: PriceSource,
+}
+
+/// Normalize country codes to uppercase ISO codes, rejecting malformed ones
+pub fn normalize_countries(countries: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(countries.len());
+    for country in countries {
+        let code = country.trim().to_uppercase();
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("'{}' is not a two-letter country code", country));
+        }
+        if !normalized.contains(&code) {
+            normalized.push(code);
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_countries() {
+        let countries = vec!["de".to_string(), " AT".to_string(), "DE".to_string()];
+        assert_eq!(normalize_countries(&countries).unwrap(), vec!["DE", "AT"]);
+        assert!(normalize_countries(&["Germany".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_applies_to_region() {
+        let mut list = PriceList {
+            id: Uuid::new_v4(),
+            name: "EU".to_string(),
+            currency: Currency::EUR,
+            countries: Vec::new(),
+            priority: 0,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(list.applies_to(None));
+
+        list.countries = vec!["DE".to_string(), "AT".to_string()];
+        assert!(list.applies_to(Some("de")));
+        assert!(!list.applies_to(Some("FR")));
+        assert!(!list.applies_to(None));
+    }
+}
@@ -0,0 +1,145 @@
+//! Print batch models
+//!
+//! A print batch is one PDF holding the shipping labels or packing slips
+//! of many shipments, laid out for a printer profile (`[printing]`). The
+//! print station marks the batch printed, or failed with the printer's
+//! error so it can be printed again.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::common::Address;
+
+/// What a print batch holds, one per shipment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "print_document_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PrintDocumentKind {
+    ShippingLabel,
+    PackingSlip,
+}
+
+impl std::fmt::Display for PrintDocumentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PrintDocumentKind::ShippingLabel => "shipping_label",
+            PrintDocumentKind::PackingSlip => "packing_slip",
+        })
+    }
+}
+
+impl std::str::FromStr for PrintDocumentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "shipping_label" | "label" | "labels" => Ok(PrintDocumentKind::ShippingLabel),
+            "packing_slip" | "packing_slips" | "slips" => Ok(PrintDocumentKind::PackingSlip),
+            _ => Err(format!("Unknown document '{}' (labels, packing_slips)", s)),
+        }
+    }
+}
+
+/// Where a print batch is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "print_batch_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PrintBatchStatus {
+    /// The PDF is ready to print
+    Generated,
+    Printed,
+    /// The printer failed; the batch can be printed again
+    Failed,
+}
+
+/// A merged PDF of documents for a printer profile
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PrintBatch {
+    pub id: Uuid,
+    pub document_kind: PrintDocumentKind,
+    pub profile: String,
+    pub status: PrintBatchStatus,
+    pub document_count: i32,
+    pub page_count: i32,
+    /// The printer's error when the batch failed
+    pub error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub printed_at: Option<DateTime<Utc>>,
+    /// Shipments in print order
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub shipment_ids: Vec<Uuid>,
+}
+
+/// Request to print the documents of shipments as one batch
+///
+/// Lists `shipment_ids`, or selects shipments with a label that has not
+/// been printed (`unprinted`), optionally from one inventory location.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreatePrintBatchRequest {
+    #[serde(default)]
+    pub shipment_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub unprinted: bool,
+    /// Code of the location shipments leave from (with `unprinted`)
+    pub location: Option<String>,
+    /// Most shipments selected with `unprinted` (default `printing.max_batch_size`)
+    pub limit: Option<usize>,
+    /// Default: shipping labels
+    pub document_kind: Option<PrintDocumentKind>,
+    /// Default: `printing.label_profile` for labels, else `printing.document_profile`
+    pub profile: Option<String>,
+}
+
+/// Request to record that printing a batch failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintBatchFailedRequest {
+    pub error: String,
+}
+
+/// A shipment with what its label and packing slip show
+#[derive(Debug, Clone)]
+pub struct PrintableShipment {
+    pub shipment_id: Uuid,
+    pub order_number: String,
+    pub location_code: Option<String>,
+    pub carrier: Option<String>,
+    pub service_code: Option<String>,
+    pub tracking_number: Option<String>,
+    pub label_url: Option<String>,
+    pub ship_to: Option<Address>,
+    pub lines: Vec<PrintableLine>,
+}
+
+/// Units of an order line in a shipment
+#[derive(Debug, Clone, FromRow)]
+pub struct PrintableLine {
+    pub title: String,
+    pub variant_title: Option<String>,
+    pub sku: Option<String>,
+    pub quantity: i32,
+}
+
+/// Filter for listing print batches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PrintBatchFilter {
+    pub status: Option<PrintBatchStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document_kind() {
+        assert_eq!("labels".parse::<PrintDocumentKind>(), Ok(PrintDocumentKind::ShippingLabel));
+        assert_eq!("packing-slip".parse::<PrintDocumentKind>(), Ok(PrintDocumentKind::PackingSlip));
+        assert!("invoices".parse::<PrintDocumentKind>().is_err());
+    }
+}
@@ -5,7 +5,7 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
-use super::{Currency, InventoryPolicy, WeightUnit};
+use super::{AttributeCondition, Currency, InventoryPolicy, WeightUnit};
 
 /// Product type - determines how the product is sold
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, Default)]
@@ -95,6 +95,9 @@ pub struct Product {
     // Bundle product fields
     pub bundle_pricing_strategy: Option<BundlePricingStrategy>,
     pub bundle_discount_percentage: Option<Decimal>,
+    /// Brand or manufacturer, for smart collection rules and filters
+    #[sqlx(default)]
+    pub vendor: Option<String>,
 }
 
 /// Product variant
@@ -116,6 +119,35 @@ pub struct ProductVariant {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// GTIN/EAN/UPC or internal barcode
+    pub barcode: Option<String>,
+}
+
+/// An option value selected by a variant, e.g. Size = "M"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate)]
+pub struct VariantOption {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    
+    #[validate(length(min = 1, max = 100))]
+    pub value: String,
+}
+
+/// Variant with its option values, in option position order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductVariantWithOptions {
+    #[serde(flatten)]
+    pub variant: ProductVariant,
+    pub options: Vec<VariantOption>,
+}
+
+/// A product option with the values its variants use, e.g. Size: S, M, L
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductOptionSet {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub values: Vec<String>,
 }
 
 /// Product image
@@ -155,46 +187,6 @@ pub struct ProductOptionValue {
     pub updated_at: DateTime<Utc>,
 }
 
-/// Product attribute definition (for variable products)
-/// e.g., "Color" with values ["Red", "Blue", "Green"]
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct ProductAttribute {
-    pub id: Uuid,
-    pub product_id: Uuid,
-    pub name: String,           // e.g., "Color", "Size"
-    pub slug: String,           // e.g., "color", "size"
-    pub position: i32,          // Display order
-    pub visible: bool,          // Show on product page
-    pub variation: bool,        // Used for variations
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
-/// Product attribute value
-/// e.g., Color="Red"
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct ProductAttributeValue {
-    pub id: Uuid,
-    pub attribute_id: Uuid,
-    pub value: String,          // e.g., "Red", "XL"
-    pub color_code: Option<String>, // Hex color code for color attributes
-    pub image_url: Option<String>,  // Swatch image
-    pub position: i32,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
-/// Links variants to their attribute values
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct ProductVariantAttribute {
-    pub id: Uuid,
-    pub variant_id: Uuid,
-    pub attribute_value_id: Uuid,
-    pub value: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
 /// Bundle component - links a bundle product to its component products
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct BundleComponent {
@@ -258,6 +250,10 @@ pub struct CreateProductRequest {
     
     pub seo_description: Option<String>,
     
+    #[serde(default)]
+    #[validate(length(max = 255))]
+    pub vendor: Option<String>,
+    
     // Subscription fields (required when product_type is Subscription)
     pub subscription_interval: Option<SubscriptionInterval>,
     pub subscription_interval_count: Option<i32>,
@@ -266,9 +262,6 @@ pub struct CreateProductRequest {
     pub subscription_min_cycles: Option<i32>,
     pub subscription_max_cycles: Option<i32>,
     
-    // For variable products - attributes like Size, Color
-    pub attributes: Option<Vec<CreateAttributeRequest>>,
-    
     // Digital product fields
     pub file_url: Option<String>,
     pub file_size: Option<i64>,
@@ -328,6 +321,10 @@ pub struct UpdateProductRequest {
     
     pub seo_description: Option<Option<String>>,
     
+    #[serde(default)]
+    #[validate(length(max = 255))]
+    pub vendor: Option<Option<String>>,
+    
     pub product_type: Option<ProductType>,
     
     pub subscription_interval: Option<Option<SubscriptionInterval>>,
@@ -350,68 +347,88 @@ pub struct UpdateProductRequest {
     pub bundle_discount_percentage: Option<Option<Decimal>>,
 }
 
-/// Create attribute request (for variable products)
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
-pub struct CreateAttributeRequest {
-    #[validate(length(min = 1, max = 100))]
-    pub name: String,           // e.g., "Color", "Size"
-    
-    #[validate(length(min = 1, max = 100))]
-    pub slug: String,           // e.g., "color", "size"
-    
-    pub position: i32,
-    
-    pub visible: bool,
-    
-    pub variation: bool,        // Used for creating variations
-    
-    pub values: Vec<CreateAttributeValueRequest>,
-}
-
-/// Create attribute value request
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
-pub struct CreateAttributeValueRequest {
-    #[validate(length(min = 1, max = 100))]
-    pub value: String,          // e.g., "Red", "XL"
-    
-    pub color_code: Option<String>, // Hex code for colors
-    
-    pub image_url: Option<String>,  // Swatch image
-    
-    pub position: i32,
-}
-
 /// Create variant request
+/// 
+/// Options are matched to the product's options by name; new option names
+/// are added to the product. Currency defaults to the product's.
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateVariantRequest {
-    pub product_id: Uuid,
-    
     #[validate(length(min = 1, max = 255))]
-    pub title: String,
+    pub title: Option<String>,
     
     #[validate(length(max = 100))]
     pub sku: Option<String>,
     
+    #[validate(length(max = 100))]
+    pub barcode: Option<String>,
+    
     pub price: Decimal,
     
     pub compare_at_price: Option<Decimal>,
     
     pub cost_price: Option<Decimal>,
     
-    pub currency: Currency,
+    pub currency: Option<Currency>,
     
+    #[serde(default)]
     #[validate(range(min = 0))]
     pub inventory_quantity: i32,
     
+    #[serde(default)]
     pub inventory_policy: InventoryPolicy,
     
     pub weight: Option<Decimal>,
     
     pub weight_unit: Option<WeightUnit>,
     
+    #[serde(default = "default_true")]
     pub requires_shipping: bool,
     
+    #[serde(default = "default_true")]
     pub is_active: bool,
+    
+    #[serde(default)]
+    #[validate]
+    pub options: Vec<VariantOption>,
+}
+
+/// Update variant request; `options` replaces all of the variant's option values
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateVariantRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    
+    #[validate(length(max = 100))]
+    pub sku: Option<Option<String>>,
+    
+    #[validate(length(max = 100))]
+    pub barcode: Option<Option<String>>,
+    
+    pub price: Option<Decimal>,
+    
+    pub compare_at_price: Option<Option<Decimal>>,
+    
+    pub cost_price: Option<Option<Decimal>>,
+    
+    #[validate(range(min = 0))]
+    pub inventory_quantity: Option<i32>,
+    
+    pub inventory_policy: Option<InventoryPolicy>,
+    
+    pub weight: Option<Option<Decimal>>,
+    
+    pub weight_unit: Option<Option<WeightUnit>>,
+    
+    pub requires_shipping: Option<bool>,
+    
+    pub is_active: Option<bool>,
+    
+    #[validate]
+    pub options: Option<Vec<VariantOption>>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Create bundle component request
@@ -460,6 +477,9 @@ pub struct ProductFilter {
     pub inventory_status: Option<InventoryStatus>,
     pub created_after: Option<DateTime<Utc>>,
     pub updated_after: Option<DateTime<Utc>>,
+    /// Products matching every condition, by their own or a variant's values
+    #[serde(default)]
+    pub attributes: Vec<AttributeCondition>,
 }
 
 /// Inventory status filter
@@ -471,24 +491,6 @@ pub enum InventoryStatus {
     OnBackorder,
 }
 
-/// Product collection
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct Collection {
-    pub id: Uuid,
-    pub title: String,
-    pub handle: String,
-    pub description: Option<String>,
-    pub seo_title: Option<String>,
-    pub seo_description: Option<String>,
-    pub sort_order: String, // manual, best-selling, created, etc.
-    pub published_at: Option<DateTime<Utc>>,
-    pub template_suffix: Option<String>,
-    pub disjunctive: bool,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    pub published_scope: String, // web, global
-}
-
 /// Order item download tracking
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct OrderItemDownload {
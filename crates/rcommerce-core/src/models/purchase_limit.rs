@@ -0,0 +1,365 @@
+//! Purchase limit and allocation list models
+//!
+//! A purchase limit rule caps the units of a product (or one variant) a
+//! customer can buy, for all time or within a rolling window, optionally
+//! counting everything shipped to the same household. An allocation list
+//! holds a product back for its members until it goes public. Both are
+//! checked when items are added to a cart and again at checkout; a refused
+//! purchase is a validation error carrying one of the `PurchaseLimitViolation`
+//! codes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::{Address, CartItem};
+use crate::error::ValidationErrors;
+use crate::Error;
+
+/// A cap on how much of a product one buyer can purchase
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PurchaseLimitRule {
+    pub id: Uuid,
+    pub name: String,
+    pub product_id: Uuid,
+    /// None covers every variant of the product
+    pub variant_id: Option<Uuid>,
+    pub max_quantity: i32,
+    /// Rolling window; None counts every past order
+    pub window_hours: Option<i32>,
+    /// Also count orders shipped to the buyer's address
+    pub per_household: bool,
+    pub is_active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PurchaseLimitRule {
+    /// Whether the rule applies to a product/variant
+    pub fn covers(&self, product_id: Uuid, variant_id: Option<Uuid>) -> bool {
+        self.product_id == product_id && (self.variant_id.is_none() || self.variant_id == variant_id)
+    }
+
+    /// Units of `lines` the rule applies to
+    pub fn wanted(&self, lines: &[PurchaseLine]) -> i64 {
+        lines
+            .iter()
+            .filter(|line| self.covers(line.product_id, line.variant_id))
+            .map(|line| line.quantity)
+            .sum()
+    }
+
+    /// Why buying `wanted` more units is refused after `bought`, if it is
+    pub fn violation(&self, wanted: i64, bought: i64) -> Option<PurchaseLimitViolation> {
+        let max_quantity = i64::from(self.max_quantity);
+        if wanted <= 0 || bought + wanted <= max_quantity {
+            return None;
+        }
+        Some(PurchaseLimitViolation::LimitExceeded {
+            product_id: self.product_id,
+            max_quantity,
+            remaining: (max_quantity - bought).max(0),
+            window_hours: self.window_hours,
+            per_household: self.per_household,
+        })
+    }
+}
+
+/// A product held back for a list of customers
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AllocationList {
+    pub id: Uuid,
+    pub name: String,
+    pub product_id: Uuid,
+    /// None covers every variant of the product
+    pub variant_id: Option<Uuid>,
+    /// Members can buy from here
+    pub opens_at: DateTime<Utc>,
+    /// Everyone can buy from here; None keeps the product members-only
+    pub public_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AllocationList {
+    /// Whether the list applies to a product/variant
+    pub fn covers(&self, product_id: Uuid, variant_id: Option<Uuid>) -> bool {
+        self.product_id == product_id && (self.variant_id.is_none() || self.variant_id == variant_id)
+    }
+
+    /// Why a buyer cannot buy the product at `now`, if they cannot
+    pub fn violation(&self, is_member: bool, now: DateTime<Utc>) -> Option<PurchaseLimitViolation> {
+        if !self.is_active || self.public_at.is_some_and(|public_at| public_at <= now) {
+            return None;
+        }
+        if is_member {
+            return (now < self.opens_at).then_some(PurchaseLimitViolation::NotYetAvailable {
+                product_id: self.product_id,
+                opens_at: self.opens_at,
+            });
+        }
+        Some(PurchaseLimitViolation::EarlyAccessOnly {
+            product_id: self.product_id,
+            public_at: self.public_at,
+        })
+    }
+}
+
+/// A customer on an allocation list
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AllocationListMember {
+    pub customer_id: Uuid,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// An allocation list with its member count
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationListDetails {
+    #[serde(flatten)]
+    pub list: AllocationList,
+    pub member_count: i64,
+}
+
+/// Request to define a purchase limit rule
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePurchaseLimitRuleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    #[validate(range(min = 1))]
+    pub max_quantity: i32,
+    #[validate(range(min = 1))]
+    pub window_hours: Option<i32>,
+    #[serde(default)]
+    pub per_household: bool,
+}
+
+/// Request to change a purchase limit rule; omitted fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePurchaseLimitRuleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(range(min = 1))]
+    pub max_quantity: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// Request to define an allocation list
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateAllocationListRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    /// Default: now
+    pub opens_at: Option<DateTime<Utc>>,
+    pub public_at: Option<DateTime<Utc>>,
+}
+
+/// Request to add customers to an allocation list, by id or by email
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct AddAllocationMembersRequest {
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub customer_ids: Vec<Uuid>,
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub emails: Vec<String>,
+}
+
+/// Units of one product/variant being bought
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PurchaseLine {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity: i64,
+}
+
+impl From<&CartItem> for PurchaseLine {
+    fn from(item: &CartItem) -> Self {
+        Self {
+            product_id: item.product_id,
+            variant_id: item.variant_id,
+            quantity: i64::from(item.quantity),
+        }
+    }
+}
+
+/// Who is buying, as far as limits can tell
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Buyer {
+    pub customer_id: Option<Uuid>,
+    /// Matches guest orders placed with the same email
+    pub email: Option<String>,
+    /// `household_key` of the shipping address, once it is known
+    pub household_key: Option<String>,
+}
+
+/// Why a purchase was refused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PurchaseLimitViolation {
+    /// Buying more would go over a purchase limit rule
+    LimitExceeded {
+        product_id: Uuid,
+        max_quantity: i64,
+        remaining: i64,
+        window_hours: Option<i32>,
+        per_household: bool,
+    },
+    /// The product is not on sale yet
+    NotYetAvailable { product_id: Uuid, opens_at: DateTime<Utc> },
+    /// Only members of an allocation list can buy the product for now
+    EarlyAccessOnly { product_id: Uuid, public_at: Option<DateTime<Utc>> },
+}
+
+impl PurchaseLimitViolation {
+    /// Stable error code for clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            PurchaseLimitViolation::LimitExceeded { per_household: false, .. } => "purchase_limit_exceeded",
+            PurchaseLimitViolation::LimitExceeded { per_household: true, .. } => "household_limit_exceeded",
+            PurchaseLimitViolation::NotYetAvailable { .. } => "not_yet_available",
+            PurchaseLimitViolation::EarlyAccessOnly { .. } => "early_access_only",
+        }
+    }
+
+    pub fn product_id(&self) -> Uuid {
+        match self {
+            PurchaseLimitViolation::LimitExceeded { product_id, .. }
+            | PurchaseLimitViolation::NotYetAvailable { product_id, .. }
+            | PurchaseLimitViolation::EarlyAccessOnly { product_id, .. } => *product_id,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            PurchaseLimitViolation::LimitExceeded { max_quantity, remaining, window_hours, per_household, .. } => {
+                let per = if *per_household { "household" } else { "customer" };
+                let window = match window_hours {
+                    Some(hours) => format!(" every {} hours", hours),
+                    None => String::new(),
+                };
+                format!(
+                    "Limit of {} per {}{}; you can buy {} more",
+                    max_quantity, per, window, remaining
+                )
+            }
+            PurchaseLimitViolation::NotYetAvailable { opens_at, .. } => {
+                format!("Not on sale until {}", opens_at.to_rfc3339())
+            }
+            PurchaseLimitViolation::EarlyAccessOnly { public_at: Some(public_at), .. } => {
+                format!("Early access only until {}", public_at.to_rfc3339())
+            }
+            PurchaseLimitViolation::EarlyAccessOnly { public_at: None, .. } => {
+                "Only available to invited customers".to_string()
+            }
+        }
+    }
+
+    /// A validation error with the product id as the field and `code()` as the code
+    pub fn into_error(self) -> Error {
+        let mut errors = ValidationErrors::new();
+        errors.add_with_code(self.product_id().to_string(), self.message(), self.code());
+        errors.into_error()
+    }
+}
+
+/// Identifies a household from a shipping address
+///
+/// Case, punctuation, common street abbreviations and apartment markers
+/// are ignored, so "12 Main Street, Apt 4B" and "12 main st #4b" match.
+/// The result is a SHA-256 hex digest so orders do not carry another copy
+/// of the address.
+pub fn household_key(address: &Address) -> String {
+    let street = format!("{} {}", address.address1, address.address2.as_deref().unwrap_or(""))
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter_map(|token| match token {
+            "" | "apartment" | "apt" | "unit" | "suite" | "ste" | "flat" | "no" | "number" => None,
+            "street" => Some("st"),
+            "avenue" => Some("ave"),
+            "road" => Some("rd"),
+            "drive" => Some("dr"),
+            "boulevard" => Some("blvd"),
+            "lane" => Some("ln"),
+            "court" => Some("ct"),
+            "place" => Some("pl"),
+            "north" => Some("n"),
+            "south" => Some("s"),
+            "east" => Some("e"),
+            "west" => Some("w"),
+            token => Some(token),
+        })
+        .collect::<String>();
+    let zip: String = address.zip.chars().filter(|c| c.is_alphanumeric()).collect();
+
+    let normalized = format!("{}|{}|{}", address.country.trim().to_uppercase(), zip.to_uppercase(), street);
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(address1: &str, address2: Option<&str>, zip: &str) -> Address {
+        Address {
+            id: Uuid::new_v4(),
+            customer_id: Uuid::new_v4(),
+            first_name: "Ada".to_string(),
+            last_name: "Lovelace".to_string(),
+            company: None,
+            phone: None,
+            address1: address1.to_string(),
+            address2: address2.map(str::to_string),
+            city: "Springfield".to_string(),
+            state: None,
+            country: "us".to_string(),
+            zip: zip.to_string(),
+            is_default_shipping: false,
+            is_default_billing: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_household_key_normalizes_addresses() {
+        let key = household_key(&address("12 Main Street", Some("Apt 4B"), "62701"));
+        assert_eq!(key, household_key(&address("12 main st #4b", None, "62701")));
+        assert_eq!(key, household_key(&address("12 MAIN ST.", Some("Unit 4b"), " 62701 ")));
+        assert_ne!(key, household_key(&address("12 Main Street", Some("Apt 5B"), "62701")));
+        assert_ne!(key, household_key(&address("12 Main Street", Some("Apt 4B"), "62702")));
+        assert_eq!(key.len(), 64);
+    }
+
+    #[test]
+    fn test_violation_codes() {
+        let product_id = Uuid::new_v4();
+        let violation = PurchaseLimitViolation::LimitExceeded {
+            product_id,
+            max_quantity: 1,
+            remaining: 0,
+            window_hours: None,
+            per_household: true,
+        };
+        assert_eq!(violation.code(), "household_limit_exceeded");
+
+        let Error::Validation(body) = violation.into_error() else {
+            panic!("expected a validation error");
+        };
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["errors"][0]["code"], "household_limit_exceeded");
+        assert_eq!(body["errors"][0]["field"], product_id.to_string());
+    }
+}
@@ -0,0 +1,116 @@
+//! URL redirect models
+//!
+//! Old product and category slugs (recorded by database triggers when a
+//! slug changes) and manual redirects both resolve a storefront path to
+//! where it lives now. See `crate::services::RedirectService`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Entity whose slug is part of its storefront URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "slug_entity_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SlugEntityType {
+    Product,
+    Category,
+}
+
+/// A slug an entity used before
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SlugHistoryEntry {
+    pub id: Uuid,
+    pub entity_type: SlugEntityType,
+    pub entity_id: Uuid,
+    pub slug: String,
+    /// When the entity stopped using the slug
+    pub changed_at: DateTime<Utc>,
+}
+
+/// An entity found by a current or old slug
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SlugLookup {
+    pub entity_id: Uuid,
+    /// The entity's slug now
+    pub slug: String,
+}
+
+/// A redirect managed by staff
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UrlRedirect {
+    pub id: Uuid,
+    /// Exact path matched, e.g. `/old-shop/summer-sale`, or a prefix ending
+    /// in `*` matching every path under it, e.g. `/blog/*`
+    pub source_path: String,
+    /// A storefront path or an absolute URL; for a `*` source, a `*` here is
+    /// replaced with the rest of the matched path
+    pub target_path: String,
+    pub status_code: i16,
+    /// Times the redirect was resolved
+    pub hits: i64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a manual redirect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUrlRedirectRequest {
+    pub source_path: String,
+    pub target_path: String,
+    /// 301 (default), 302, 307 or 308
+    pub status_code: Option<i16>,
+}
+
+impl UrlRedirect {
+    pub fn is_wildcard(&self) -> bool {
+        self.source_path.ends_with('*')
+    }
+}
+
+/// Request to change a manual redirect
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateUrlRedirectRequest {
+    pub target_path: Option<String>,
+    pub status_code: Option<i16>,
+}
+
+/// What a redirect came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectSource {
+    Manual,
+    SlugHistory,
+}
+
+/// Where a path redirects to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectResolution {
+    pub location: String,
+    pub status_code: u16,
+    pub source: RedirectSource,
+    /// The product or category an old slug belongs to
+    pub entity_type: Option<SlugEntityType>,
+    pub entity_id: Option<Uuid>,
+}
+
+/// A CSV row that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectImportError {
+    /// Line in the file, counting the header as line 1
+    pub line: usize,
+    pub message: String,
+}
+
+/// Outcome of a redirect CSV import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedirectImportResult {
+    pub created: u64,
+    pub updated: u64,
+    /// Rows whose source already had a redirect, when not overwriting
+    pub skipped: u64,
+    pub errors: Vec<RedirectImportError>,
+}
@@ -0,0 +1,412 @@
+//! POS register models
+//!
+//! A register session covers one drawer shift: it is opened with a cash
+//! float, records the tenders taken and refunded at the register and any
+//! cash paid in or out of the drawer, and is closed with the counted cash.
+//! Expected cash is the float plus net cash taken plus paid in, less paid
+//! out; the difference from the count is the session's over/short.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::Currency;
+
+/// Tender used for cash and for drawer paid in/out
+pub const CASH_METHOD: &str = "cash";
+
+/// Register session status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "register_session_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterSessionStatus {
+    Open,
+    Closed,
+}
+
+/// Kind of register transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "register_transaction_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterTransactionKind {
+    /// Payment taken for a sale
+    Sale,
+    /// Money returned to a customer
+    Refund,
+    /// Cash added to the drawer, e.g. change from the bank
+    PaidIn,
+    /// Cash taken from the drawer, e.g. a petty cash purchase
+    PaidOut,
+}
+
+impl RegisterTransactionKind {
+    /// Whether the transaction only moves drawer cash
+    pub fn is_drawer_movement(&self) -> bool {
+        matches!(self, Self::PaidIn | Self::PaidOut)
+    }
+}
+
+/// A POS register (cash drawer)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PosRegister {
+    pub id: Uuid,
+    pub name: String,
+    pub location_id: Option<Uuid>,
+    pub currency: Currency,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A register session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RegisterSession {
+    pub id: Uuid,
+    pub register_id: Uuid,
+    pub status: RegisterSessionStatus,
+    pub currency: Currency,
+    pub opening_float: Decimal,
+    pub opened_by: Uuid,
+    pub opened_at: DateTime<Utc>,
+    pub opening_notes: Option<String>,
+    pub counted_cash: Option<Decimal>,
+    pub expected_cash: Option<Decimal>,
+    /// Counted minus expected cash; negative when the drawer is short
+    pub over_short: Option<Decimal>,
+    pub closed_by: Option<Uuid>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closing_notes: Option<String>,
+}
+
+/// A payment, refund or drawer movement recorded in a session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RegisterTransaction {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub kind: RegisterTransactionKind,
+    pub method: String,
+    pub amount: Decimal,
+    pub order_id: Option<Uuid>,
+    pub reference: Option<String>,
+    pub recorded_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to create a register
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateRegisterRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub location_id: Option<Uuid>,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+/// Request to open a session on a register
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRegisterSessionRequest {
+    pub opening_float: Decimal,
+    pub notes: Option<String>,
+}
+
+/// Request to record a register transaction
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RecordRegisterTransactionRequest {
+    pub kind: RegisterTransactionKind,
+    /// Tender, e.g. `cash`, `card`, `gift_card`; ignored for paid in/out
+    #[validate(length(min = 1, max = 50))]
+    pub method: Option<String>,
+    pub amount: Decimal,
+    pub order_id: Option<Uuid>,
+    #[validate(length(max = 255))]
+    pub reference: Option<String>,
+}
+
+impl RecordRegisterTransactionRequest {
+    /// Normalized tender: lowercase, and always cash for drawer movements
+    pub fn tender(&self) -> Option<String> {
+        if self.kind.is_drawer_movement() {
+            return Some(CASH_METHOD.to_string());
+        }
+        self.method
+            .as_deref()
+            .map(|m| m.trim().to_lowercase())
+            .filter(|m| !m.is_empty())
+    }
+}
+
+/// Request to close a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloseRegisterSessionRequest {
+    pub counted_cash: Decimal,
+    pub notes: Option<String>,
+}
+
+/// Session totals for one tender
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodTotal {
+    pub method: String,
+    pub sales: Decimal,
+    pub refunds: Decimal,
+    /// Sales less refunds
+    pub net: Decimal,
+    pub transactions: i64,
+}
+
+/// End-of-session (Z) report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZReport {
+    pub session_id: Uuid,
+    pub register_id: Uuid,
+    pub register_name: String,
+    pub status: RegisterSessionStatus,
+    pub currency: Currency,
+    pub opened_by: Uuid,
+    pub opened_at: DateTime<Utc>,
+    pub closed_by: Option<Uuid>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub opening_float: Decimal,
+    /// Totals per tender, ordered by method
+    pub methods: Vec<MethodTotal>,
+    pub gross_sales: Decimal,
+    pub refunds: Decimal,
+    pub net_sales: Decimal,
+    pub paid_in: Decimal,
+    pub paid_out: Decimal,
+    pub expected_cash: Decimal,
+    pub counted_cash: Option<Decimal>,
+    pub over_short: Option<Decimal>,
+    pub transactions: i64,
+}
+
+impl ZReport {
+    /// Build a report from a session and its transactions. For open
+    /// sessions this is the running (X) total and has no count.
+    pub fn build(session: &RegisterSession, register_name: &str, transactions: &[RegisterTransaction]) -> Self {
+        let mut methods: Vec<MethodTotal> = Vec::new();
+        let mut paid_in = Decimal::ZERO;
+        let mut paid_out = Decimal::ZERO;
+
+        for tx in transactions {
+            match tx.kind {
+                RegisterTransactionKind::PaidIn => paid_in += tx.amount,
+                RegisterTransactionKind::PaidOut => paid_out += tx.amount,
+                RegisterTransactionKind::Sale | RegisterTransactionKind::Refund => {
+                    let total = match methods.iter_mut().find(|m| m.method == tx.method) {
+                        Some(total) => total,
+                        None => {
+                            methods.push(MethodTotal {
+                                method: tx.method.clone(),
+                                sales: Decimal::ZERO,
+                                refunds: Decimal::ZERO,
+                                net: Decimal::ZERO,
+                                transactions: 0,
+                            });
+                            methods.last_mut().expect("just pushed")
+                        }
+                    };
+                    if tx.kind == RegisterTransactionKind::Sale {
+                        total.sales += tx.amount;
+                        total.net += tx.amount;
+                    } else {
+                        total.refunds += tx.amount;
+                        total.net -= tx.amount;
+                    }
+                    total.transactions += 1;
+                }
+            }
+        }
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+
+        let gross_sales: Decimal = methods.iter().map(|m| m.sales).sum();
+        let refunds: Decimal = methods.iter().map(|m| m.refunds).sum();
+        let net_cash = methods
+            .iter()
+            .find(|m| m.method == CASH_METHOD)
+            .map(|m| m.net)
+            .unwrap_or(Decimal::ZERO);
+        let expected_cash = session.opening_float + net_cash + paid_in - paid_out;
+
+        Self {
+            session_id: session.id,
+            register_id: session.register_id,
+            register_name: register_name.to_string(),
+            status: session.status,
+            currency: session.currency,
+            opened_by: session.opened_by,
+            opened_at: session.opened_at,
+            closed_by: session.closed_by,
+            closed_at: session.closed_at,
+            opening_float: session.opening_float,
+            methods,
+            gross_sales,
+            refunds,
+            net_sales: gross_sales - refunds,
+            paid_in,
+            paid_out,
+            expected_cash,
+            counted_cash: session.counted_cash,
+            over_short: session.counted_cash.map(|counted| counted - expected_cash),
+            transactions: transactions.len() as i64,
+        }
+    }
+
+    /// Export as `section,item,value` CSV rows
+    pub fn to_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["section", "item", "value"])?;
+
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let summary = [
+            ("register", self.register_name.clone()),
+            ("session_id", self.session_id.to_string()),
+            ("status", format!("{:?}", self.status).to_lowercase()),
+            ("currency", self.currency.to_string()),
+            ("opened_by", self.opened_by.to_string()),
+            ("opened_at", self.opened_at.to_rfc3339()),
+            ("closed_by", optional(self.closed_by.map(|id| id.to_string()))),
+            ("closed_at", optional(self.closed_at.map(|at| at.to_rfc3339()))),
+            ("transactions", self.transactions.to_string()),
+        ];
+        for (item, value) in summary {
+            writer.write_record(["session", item, value.as_str()])?;
+        }
+
+        for method in &self.methods {
+            let section = format!("tender:{}", method.method);
+            writer.write_record([section.as_str(), "sales", &method.sales.to_string()])?;
+            writer.write_record([section.as_str(), "refunds", &method.refunds.to_string()])?;
+            writer.write_record([section.as_str(), "net", &method.net.to_string()])?;
+            writer.write_record([section.as_str(), "transactions", &method.transactions.to_string()])?;
+        }
+
+        let totals = [
+            ("gross_sales", self.gross_sales.to_string()),
+            ("refunds", self.refunds.to_string()),
+            ("net_sales", self.net_sales.to_string()),
+        ];
+        for (item, value) in totals {
+            writer.write_record(["totals", item, value.as_str()])?;
+        }
+
+        let drawer = [
+            ("opening_float", self.opening_float.to_string()),
+            ("paid_in", self.paid_in.to_string()),
+            ("paid_out", self.paid_out.to_string()),
+            ("expected_cash", self.expected_cash.to_string()),
+            ("counted_cash", optional(self.counted_cash.map(|c| c.to_string()))),
+            ("over_short", optional(self.over_short.map(|o| o.to_string()))),
+        ];
+        for (item, value) in drawer {
+            writer.write_record(["drawer", item, value.as_str()])?;
+        }
+
+        let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn session(counted_cash: Option<Decimal>) -> RegisterSession {
+        RegisterSession {
+            id: Uuid::new_v4(),
+            register_id: Uuid::new_v4(),
+            status: if counted_cash.is_some() { RegisterSessionStatus::Closed } else { RegisterSessionStatus::Open },
+            currency: Currency::USD,
+            opening_float: dec!(100.00),
+            opened_by: Uuid::new_v4(),
+            opened_at: Utc::now(),
+            opening_notes: None,
+            counted_cash,
+            expected_cash: None,
+            over_short: None,
+            closed_by: None,
+            closed_at: None,
+            closing_notes: None,
+        }
+    }
+
+    fn tx(session_id: Uuid, kind: RegisterTransactionKind, method: &str, amount: Decimal) -> RegisterTransaction {
+        RegisterTransaction {
+            id: Uuid::new_v4(),
+            session_id,
+            kind,
+            method: method.to_string(),
+            amount,
+            order_id: None,
+            reference: None,
+            recorded_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_z_report_totals_and_over_short() {
+        let session = session(Some(dec!(171.50)));
+        let transactions = vec![
+            tx(session.id, RegisterTransactionKind::Sale, "cash", dec!(50.00)),
+            tx(session.id, RegisterTransactionKind::Sale, "card", dec!(80.00)),
+            tx(session.id, RegisterTransactionKind::Sale, "cash", dec!(30.00)),
+            tx(session.id, RegisterTransactionKind::Refund, "cash", dec!(5.00)),
+            tx(session.id, RegisterTransactionKind::Refund, "card", dec!(10.00)),
+            tx(session.id, RegisterTransactionKind::PaidIn, "cash", dec!(20.00)),
+            tx(session.id, RegisterTransactionKind::PaidOut, "cash", dec!(12.50)),
+        ];
+
+        let report = ZReport::build(&session, "Front counter", &transactions);
+        assert_eq!(report.methods.len(), 2);
+        assert_eq!(report.methods[0].method, "card");
+        assert_eq!(report.methods[0].net, dec!(70.00));
+        assert_eq!(report.methods[1].net, dec!(75.00));
+        assert_eq!(report.methods[1].transactions, 3);
+        assert_eq!(report.gross_sales, dec!(160.00));
+        assert_eq!(report.net_sales, dec!(145.00));
+        // 100 float + 75 net cash + 20 paid in - 12.50 paid out
+        assert_eq!(report.expected_cash, dec!(182.50));
+        assert_eq!(report.over_short, Some(dec!(-11.00)));
+        assert_eq!(report.transactions, 7);
+    }
+
+    #[test]
+    fn test_open_session_has_no_over_short() {
+        let session = session(None);
+        let report = ZReport::build(&session, "Front counter", &[]);
+        assert_eq!(report.expected_cash, dec!(100.00));
+        assert_eq!(report.counted_cash, None);
+        assert_eq!(report.over_short, None);
+    }
+
+    #[test]
+    fn test_z_report_csv() {
+        let session = session(Some(dec!(150.00)));
+        let transactions = vec![tx(session.id, RegisterTransactionKind::Sale, "cash", dec!(50.00))];
+        let csv = ZReport::build(&session, "Till, \"A\"", &transactions).to_csv().unwrap();
+
+        assert!(csv.starts_with("section,item,value\n"));
+        assert!(csv.contains("session,register,\"Till, \"\"A\"\"\"\n"));
+        assert!(csv.contains("tender:cash,net,50.00\n"));
+        assert!(csv.contains("drawer,over_short,0.00\n"));
+    }
+
+    #[test]
+    fn test_drawer_movements_are_cash() {
+        let request = RecordRegisterTransactionRequest {
+            kind: RegisterTransactionKind::PaidOut,
+            method: Some("card".to_string()),
+            amount: dec!(5),
+            order_id: None,
+            reference: None,
+        };
+        assert_eq!(request.tender().as_deref(), Some("cash"));
+
+        let sale = RecordRegisterTransactionRequest { kind: RegisterTransactionKind::Sale, method: Some(" Card ".to_string()), ..request };
+        assert_eq!(sale.tender().as_deref(), Some("card"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Report subscription models
+//!
+//! A subscription emails a report to its recipients on a schedule; each
+//! report sent is kept as a delivery (see `crate::reports`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Report a subscription sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "report_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    /// Orders, items sold and revenue per day
+    SalesSummary,
+    /// Tax collected per country, region and rate
+    TaxLiability,
+    /// Inventory levels at or below their reorder point (a snapshot)
+    LowStock,
+    /// Failed payment attempts
+    FailedPayments,
+    /// Storefront sessions reaching each checkout step
+    CheckoutFunnel,
+}
+
+impl ReportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportKind::SalesSummary => "sales_summary",
+            ReportKind::TaxLiability => "tax_liability",
+            ReportKind::LowStock => "low_stock",
+            ReportKind::FailedPayments => "failed_payments",
+            ReportKind::CheckoutFunnel => "checkout_funnel",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReportKind::SalesSummary => "Sales summary",
+            ReportKind::TaxLiability => "Tax liability",
+            ReportKind::LowStock => "Low stock",
+            ReportKind::FailedPayments => "Failed payments",
+            ReportKind::CheckoutFunnel => "Checkout funnel",
+        }
+    }
+
+    /// Whether the report covers a period rather than the current state
+    pub fn has_period(&self) -> bool {
+        !matches!(self, ReportKind::LowStock)
+    }
+}
+
+impl fmt::Display for ReportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sales_summary" => Ok(ReportKind::SalesSummary),
+            "tax_liability" => Ok(ReportKind::TaxLiability),
+            "low_stock" => Ok(ReportKind::LowStock),
+            "failed_payments" => Ok(ReportKind::FailedPayments),
+            "checkout_funnel" => Ok(ReportKind::CheckoutFunnel),
+            _ => Err(format!("Unknown report: {}", s)),
+        }
+    }
+}
+
+/// How often a report is sent; each covers the day, week or month before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "report_schedule", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSchedule {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// File format of a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "report_format", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "text/csv; charset=utf-8",
+            ReportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Xlsx => "xlsx",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// A report emailed to recipients on a schedule
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReportSubscription {
+    pub id: Uuid,
+    pub name: String,
+    pub report: ReportKind,
+    pub schedule: ReportSchedule,
+    pub format: ReportFormat,
+    pub recipients: Vec<String>,
+    /// Hour of the day the report is sent (UTC)
+    pub hour: i16,
+    /// ISO weekday weekly reports are sent on (1 = Monday)
+    pub weekday: i16,
+    /// Day of the month monthly reports are sent on (1-28)
+    pub day_of_month: i16,
+    pub is_active: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to subscribe to a report
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReportSubscriptionRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub report: ReportKind,
+    pub schedule: ReportSchedule,
+    #[serde(default)]
+    pub format: ReportFormat,
+    pub recipients: Vec<String>,
+    #[serde(default = "default_hour")]
+    #[validate(range(min = 0, max = 23))]
+    pub hour: i16,
+    #[serde(default = "default_weekday")]
+    #[validate(range(min = 1, max = 7))]
+    pub weekday: i16,
+    #[serde(default = "default_day_of_month")]
+    #[validate(range(min = 1, max = 28))]
+    pub day_of_month: i16,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+/// Request to change a subscription; omitted fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateReportSubscriptionRequest {
+    pub name: Option<String>,
+    pub report: Option<ReportKind>,
+    pub schedule: Option<ReportSchedule>,
+    pub format: Option<ReportFormat>,
+    pub recipients: Option<Vec<String>>,
+    pub hour: Option<i16>,
+    pub weekday: Option<i16>,
+    pub day_of_month: Option<i16>,
+    pub is_active: Option<bool>,
+}
+
+fn default_hour() -> i16 {
+    6
+}
+
+fn default_weekday() -> i16 {
+    1
+}
+
+fn default_day_of_month() -> i16 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Whether a report was generated and emailed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "report_delivery_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportDeliveryStatus {
+    Sent,
+    /// The report could not be generated or queued; see `error`
+    Failed,
+}
+
+/// A generated report and who it was sent to
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReportDelivery {
+    pub id: Uuid,
+    pub subscription_id: Option<Uuid>,
+    pub report: ReportKind,
+    pub format: ReportFormat,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: Option<DateTime<Utc>>,
+    pub recipients: Vec<String>,
+    pub status: ReportDeliveryStatus,
+    pub error: Option<String>,
+    pub filename: String,
+    pub row_count: i32,
+    pub size_bytes: i32,
+    /// Whether the file is still kept (until `expires_at`)
+    pub available: bool,
+    #[serde(skip_serializing, default)]
+    pub download_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Times the report was emailed, counting re-sends
+    pub sent_count: i32,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to email a delivered report again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResendReportRequest {
+    /// Defaults to the delivery's recipients
+    pub recipients: Option<Vec<String>>,
+}
+
+/// A generated (or failed) report to record as a delivery
+#[derive(Debug, Clone)]
+pub struct NewReportDelivery {
+    pub subscription_id: Option<Uuid>,
+    pub report: ReportKind,
+    pub format: ReportFormat,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: Option<DateTime<Utc>>,
+    pub recipients: Vec<String>,
+    pub status: ReportDeliveryStatus,
+    pub error: Option<String>,
+    pub filename: String,
+    pub row_count: i32,
+    /// None when the report failed
+    pub content: Option<Vec<u8>>,
+    pub download_token: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,262 @@
+//! Return (RMA) models
+//!
+//! A return authorization covers some or all of the units of an order's
+//! lines. It moves requested -> approved -> received -> refunded; requests
+//! can be rejected by staff or cancelled before the parcel arrives.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Return status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "return_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnStatus {
+    /// Waiting for staff
+    Requested,
+    /// Approved; the customer ships the items back
+    Approved,
+    Rejected,
+    /// Items are back in the warehouse, awaiting refund
+    Received,
+    Refunded,
+    Cancelled,
+}
+
+impl ReturnStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ReturnStatus::Rejected | ReturnStatus::Refunded | ReturnStatus::Cancelled)
+    }
+
+    pub fn can_transition_to(&self, new_status: ReturnStatus) -> bool {
+        use ReturnStatus::*;
+
+        matches!(
+            (self, new_status),
+            (Requested, Approved)
+                | (Requested, Rejected)
+                | (Requested, Cancelled)
+                | (Approved, Received)
+                | (Approved, Cancelled)
+                | (Received, Refunded)
+        )
+    }
+}
+
+/// A return authorization
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReturnAuthorization {
+    pub id: Uuid,
+    pub rma_number: String,
+    pub order_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub status: ReturnStatus,
+    pub reason: String,
+    pub customer_notes: Option<String>,
+    /// Staff notes on the decision, shown to the customer
+    pub decision_notes: Option<String>,
+    pub currency: String,
+    /// Amount refunded once the items are received
+    pub refund_amount: Decimal,
+    pub label_provider: Option<String>,
+    pub label_service: Option<String>,
+    pub tracking_number: Option<String>,
+    pub label_url: Option<String>,
+    pub refund_id: Option<Uuid>,
+    pub gateway_refund_id: Option<String>,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub refunded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A line on a return
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReturnItem {
+    pub id: Uuid,
+    pub return_id: Uuid,
+    pub order_item_id: Uuid,
+    pub title: String,
+    pub sku: Option<String>,
+    pub quantity: i32,
+    pub unit_refund: Decimal,
+    pub reason: Option<String>,
+}
+
+/// A status change on a return
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReturnEvent {
+    pub id: Uuid,
+    pub return_id: Uuid,
+    pub status: ReturnStatus,
+    /// None for automatic steps
+    pub actor_id: Option<Uuid>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A return with its lines and history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnDetails {
+    #[serde(flatten)]
+    pub rma: ReturnAuthorization,
+    pub items: Vec<ReturnItem>,
+    pub events: Vec<ReturnEvent>,
+}
+
+/// The order a return is requested against
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReturnOrder {
+    pub id: Uuid,
+    pub order_number: String,
+    pub customer_id: Option<Uuid>,
+    pub email: String,
+    pub customer_name: Option<String>,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An order line and how many of its units can still be returned
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReturnableItem {
+    pub order_item_id: Uuid,
+    pub title: String,
+    pub sku: Option<String>,
+    pub quantity: i32,
+    /// Units on returns that were not rejected or cancelled
+    pub returned_quantity: i32,
+    /// Line total (with tax) over its quantity
+    pub unit_refund: Decimal,
+}
+
+impl ReturnableItem {
+    pub fn returnable_quantity(&self) -> i32 {
+        (self.quantity - self.returned_quantity).max(0)
+    }
+}
+
+/// A line to return
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReturnItem {
+    pub order_item_id: Uuid,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    #[validate(length(max = 255))]
+    pub reason: Option<String>,
+}
+
+/// Request to return items from an order
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateReturnRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub reason: String,
+    pub customer_notes: Option<String>,
+    #[validate]
+    #[validate(length(min = 1))]
+    pub items: Vec<CreateReturnItem>,
+}
+
+/// A checked line, priced for the refund
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewReturnItem {
+    pub order_item_id: Uuid,
+    pub quantity: i32,
+    pub unit_refund: Decimal,
+    pub reason: Option<String>,
+}
+
+impl NewReturnItem {
+    /// Check requested lines against what is left to return on the order
+    pub fn plan(returnable: &[ReturnableItem], items: &[CreateReturnItem]) -> Result<Vec<Self>, String> {
+        let mut seen = HashSet::new();
+        items
+            .iter()
+            .map(|item| {
+                if !seen.insert(item.order_item_id) {
+                    return Err(format!("Order item {} is listed more than once", item.order_item_id));
+                }
+                let line = returnable
+                    .iter()
+                    .find(|line| line.order_item_id == item.order_item_id)
+                    .ok_or_else(|| format!("Order item {} is not on this order", item.order_item_id))?;
+                if item.quantity > line.returnable_quantity() {
+                    return Err(format!(
+                        "Only {} of {} can be returned",
+                        line.returnable_quantity(),
+                        line.title
+                    ));
+                }
+                Ok(Self {
+                    order_item_id: item.order_item_id,
+                    quantity: item.quantity,
+                    unit_refund: line.unit_refund,
+                    reason: item.reason.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Refund for all planned lines
+    pub fn total(items: &[Self]) -> Decimal {
+        items.iter().map(|item| item.unit_refund * Decimal::from(item.quantity)).sum()
+    }
+}
+
+/// Approve, reject, cancel or receive a return
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReturnDecision {
+    pub notes: Option<String>,
+}
+
+/// Carrier and service for a return label, overriding `[returns]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReturnLabelRequest {
+    pub provider_id: Option<String>,
+    pub service_code: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_return_status_transitions() {
+        assert!(ReturnStatus::Requested.can_transition_to(ReturnStatus::Approved));
+        assert!(ReturnStatus::Approved.can_transition_to(ReturnStatus::Received));
+        assert!(ReturnStatus::Received.can_transition_to(ReturnStatus::Refunded));
+        assert!(!ReturnStatus::Requested.can_transition_to(ReturnStatus::Refunded));
+        assert!(!ReturnStatus::Received.can_transition_to(ReturnStatus::Cancelled));
+        assert!(!ReturnStatus::Rejected.can_transition_to(ReturnStatus::Approved));
+        assert!(ReturnStatus::Refunded.is_terminal());
+    }
+
+    #[test]
+    fn test_plan_return_items() {
+        let mug = Uuid::new_v4();
+        let returnable = vec![ReturnableItem {
+            order_item_id: mug,
+            title: "Mug".to_string(),
+            sku: None,
+            quantity: 3,
+            returned_quantity: 1,
+            unit_refund: dec!(10.80),
+        }];
+        let item = |order_item_id, quantity| CreateReturnItem { order_item_id, quantity, reason: None };
+
+        let planned = NewReturnItem::plan(&returnable, &[item(mug, 2)]).unwrap();
+        assert_eq!(NewReturnItem::total(&planned), dec!(21.60));
+
+        assert!(NewReturnItem::plan(&returnable, &[item(mug, 3)]).is_err());
+        assert!(NewReturnItem::plan(&returnable, &[item(Uuid::new_v4(), 1)]).is_err());
+        assert!(NewReturnItem::plan(&returnable, &[item(mug, 1), item(mug, 1)]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Staff role models
+//!
+//! Roles grant `resource:action` permissions in the API key scope format
+//! (see `services::api_key_scopes`) and are assigned to customer accounts
+//! on top of their base `CustomerRole`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::CustomerRole;
+
+/// A named set of permissions
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Role {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// Scope strings such as `orders:write` or `admin`
+    pub permissions: Vec<String>,
+    /// Seeded roles cannot be deleted
+    pub is_system: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A role held by a customer account
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RoleAssignment {
+    pub customer_id: Uuid,
+    pub role_id: Uuid,
+    pub role_name: String,
+    pub permissions: Vec<String>,
+    pub assigned_by: Option<Uuid>,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// What an account may do: its base role plus assigned roles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectivePermissions {
+    pub customer_id: Uuid,
+    pub base_role: CustomerRole,
+    pub roles: Vec<RoleAssignment>,
+    /// Union of the base role's and assigned roles' permissions, sorted
+    pub permissions: Vec<String>,
+}
+
+impl EffectivePermissions {
+    pub fn new(customer_id: Uuid, base_role: CustomerRole, roles: Vec<RoleAssignment>) -> Self {
+        let mut permissions: Vec<String> = base_role
+            .permissions()
+            .into_iter()
+            .chain(roles.iter().flat_map(|role| role.permissions.iter().cloned()))
+            .collect();
+        permissions.sort();
+        permissions.dedup();
+
+        Self {
+            customer_id,
+            base_role,
+            roles,
+            permissions,
+        }
+    }
+}
+
+/// Request to create a role
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateRoleRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub description: Option<String>,
+    #[validate(length(min = 1))]
+    pub permissions: Vec<String>,
+}
+
+/// Request to update a role
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateRoleRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[validate(length(min = 1))]
+    pub permissions: Option<Vec<String>>,
+}
+
+/// Request to assign a role to an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignRoleRequest {
+    pub role_id: Uuid,
+}
+
+/// What a permissions preview evaluated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PreviewSubject {
+    Role { id: Uuid, name: String },
+    ApiKey { prefix: String, name: String, key_type: String },
+    Customer { id: Uuid },
+    /// An ad hoc permission list, e.g. a role before it is created
+    Permissions,
+}
+
+/// Access to one resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceAccess {
+    pub resource: String,
+    pub read: bool,
+    pub write: bool,
+    pub admin: bool,
+}
+
+/// Access to one group of admin routes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminRouteAccess {
+    /// Route prefix under `/api/v1`
+    pub path: String,
+    pub resource: String,
+    /// GET requests
+    pub read: bool,
+    /// POST, PUT, PATCH and DELETE requests
+    pub write: bool,
+}
+
+/// What a role, API key or account would be allowed to do
+///
+/// Evaluated without acting as the subject, so previewing is safe for
+/// keys and accounts the caller must not use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionPreview {
+    pub subject: PreviewSubject,
+    pub permissions: Vec<String>,
+    /// Holds the global `admin` permission
+    pub is_admin: bool,
+    pub resources: Vec<ResourceAccess>,
+    /// Empty for API keys, which cannot call admin routes
+    pub admin_routes: Vec<AdminRouteAccess>,
+    /// Caveats such as revoked keys or unrecognised scopes
+    pub notes: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_permissions_merge_roles() {
+        let customer_id = Uuid::new_v4();
+        let role = |name: &str, permissions: &[&str]| RoleAssignment {
+            customer_id,
+            role_id: Uuid::new_v4(),
+            role_name: name.to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            assigned_by: None,
+            assigned_at: Utc::now(),
+        };
+
+        let effective = EffectivePermissions::new(
+            customer_id,
+            CustomerRole::Customer,
+            vec![
+                role("support_agent", &["orders:write", "customers:write"]),
+                role("analyst", &["reports:read", "orders:write"]),
+            ],
+        );
+        assert_eq!(
+            effective.permissions,
+            vec!["customers:write", "orders:write", "read", "reports:read"]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Scheduled job models
+//!
+//! A recurring job's schedule, lease and outcome of its last run, as kept
+//! in `scheduled_jobs`, and the dead-lettered jobs that ran out of retries.
+//! See `jobs::recurring` for the scheduler.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Outcome of a job's last run, as stored in `last_status`
+pub const JOB_SUCCEEDED: &str = "succeeded";
+pub const JOB_FAILED: &str = "failed";
+
+/// A recurring job
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub description: String,
+    /// Cron expression
+    pub schedule: String,
+    pub paused: bool,
+    /// A one-off run was asked for
+    pub run_requested: bool,
+    pub next_run_at: DateTime<Utc>,
+    /// Instance holding the lease while the job runs
+    pub locked_by: Option<String>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    /// `succeeded` or `failed`
+    pub last_status: Option<String>,
+    pub last_message: Option<String>,
+    pub last_duration_ms: Option<i64>,
+    /// Failed runs since the last success, retried with backoff
+    pub failed_attempts: i32,
+    pub failures: Json<Vec<JobFailure>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScheduledJob {
+    /// Whether an instance holds the lease at `now`
+    pub fn is_running(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Status of a dead letter awaiting a retry or discard
+pub const DEAD_LETTER_DEAD: &str = "dead";
+pub const DEAD_LETTER_RETRIED: &str = "retried";
+pub const DEAD_LETTER_DISCARDED: &str = "discarded";
+
+/// A failed run of a recurring job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobFailure {
+    /// 1 for the first run, then each retry
+    pub attempt: i32,
+    pub error: String,
+    /// Instance the run failed on
+    pub instance: String,
+    pub duration_ms: i64,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// A job that failed every attempt its retry policy allows
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct JobDeadLetter {
+    pub id: Uuid,
+    pub job_name: String,
+    pub attempts: i32,
+    /// Error of the last attempt
+    pub error: String,
+    pub failures: Json<Vec<JobFailure>>,
+    /// `dead`, `retried` or `discarded`
+    pub status: String,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,100 @@
+//! Soft deletes
+//!
+//! Products, customers and orders are deleted by setting `deleted_at`; they
+//! stay restorable until the purge job removes them after the retention period.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A kind of record that is soft deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoftDeleteKind {
+    Products,
+    Customers,
+    Orders,
+}
+
+impl SoftDeleteKind {
+    pub const ALL: [SoftDeleteKind; 3] = [SoftDeleteKind::Products, SoftDeleteKind::Customers, SoftDeleteKind::Orders];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SoftDeleteKind::Products => "products",
+            SoftDeleteKind::Customers => "customers",
+            SoftDeleteKind::Orders => "orders",
+        }
+    }
+
+    /// The table; also the plural name used in paths and messages
+    pub fn table(&self) -> &'static str {
+        self.as_str()
+    }
+
+    /// Column shown for deleted records
+    pub fn label_column(&self) -> &'static str {
+        match self {
+            SoftDeleteKind::Products => "title",
+            SoftDeleteKind::Customers => "email",
+            SoftDeleteKind::Orders => "order_number",
+        }
+    }
+
+    /// Singular name for messages, e.g. "Product"
+    pub fn noun(&self) -> &'static str {
+        match self {
+            SoftDeleteKind::Products => "Product",
+            SoftDeleteKind::Customers => "Customer",
+            SoftDeleteKind::Orders => "Order",
+        }
+    }
+}
+
+impl std::fmt::Display for SoftDeleteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SoftDeleteKind {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let s = s.to_ascii_lowercase();
+        SoftDeleteKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s || kind.as_str().strip_suffix('s') == Some(s.as_str()))
+            .ok_or_else(|| crate::Error::validation(format!("Unknown record type '{}'; use products, customers or orders", s)))
+    }
+}
+
+/// A soft-deleted record
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DeletedRecord {
+    pub id: Uuid,
+    /// Title, email or order number
+    pub label: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// What a purge run removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SoftDeletePurge {
+    pub purged: u64,
+    /// Records still referenced (e.g. products on purchase orders), kept for now
+    pub skipped: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_parse() {
+        assert_eq!("products".parse::<SoftDeleteKind>().unwrap(), SoftDeleteKind::Products);
+        assert_eq!("Customer".parse::<SoftDeleteKind>().unwrap(), SoftDeleteKind::Customers);
+        assert!("carts".parse::<SoftDeleteKind>().is_err());
+    }
+}
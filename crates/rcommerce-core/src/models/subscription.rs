@@ -51,6 +51,7 @@ pub struct Subscription {
     pub order_id: Uuid,              // Original order that created the subscription
     pub product_id: Uuid,
     pub variant_id: Option<Uuid>,
+    pub plan_id: Option<Uuid>,       // Plan the terms were copied from
     
     // Subscription configuration
     pub status: SubscriptionStatus,
@@ -156,6 +157,9 @@ pub struct CreateSubscriptionRequest {
     pub order_id: Uuid,
     pub product_id: Uuid,
     pub variant_id: Option<Uuid>,
+    /// Set when subscribing to a plan; not accepted from API clients
+    #[serde(default, skip_deserializing)]
+    pub plan_id: Option<Uuid>,
     
     pub interval: super::SubscriptionInterval,
     #[validate(range(min = 1, max = 12))]
@@ -0,0 +1,146 @@
+//! Two-factor authentication models
+//!
+//! An account's TOTP credential, its one-time backup codes and the login
+//! challenges answered with a code after the password. Secrets and hashes
+//! are never serialized.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Prefix marking two-factor challenge tokens
+pub const TWO_FACTOR_CHALLENGE_PREFIX: &str = "tfa_";
+
+/// An account's TOTP credential
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TwoFactorCredential {
+    pub customer_id: Uuid,
+    /// Base32 secret, sealed with the master key when one is configured
+    #[serde(skip)]
+    pub secret: String,
+    /// None while enrollment waits for the first code
+    pub confirmed_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub last_used_step: Option<i64>,
+    #[serde(skip)]
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TwoFactorCredential {
+    pub fn is_enabled(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Which login a challenge completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoFactorLoginKind {
+    /// Bearer access and refresh tokens
+    Token,
+    /// Cookie session
+    Cookie,
+}
+
+impl TwoFactorLoginKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwoFactorLoginKind::Token => "token",
+            TwoFactorLoginKind::Cookie => "cookie",
+        }
+    }
+}
+
+/// A login waiting for its second factor
+#[derive(Debug, Clone, FromRow)]
+pub struct TwoFactorChallenge {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub token_hash: String,
+    pub login_kind: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub attempts: i32,
+    pub expires_at: DateTime<Utc>,
+    pub consumed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TwoFactorChallenge {
+    pub fn kind(&self) -> TwoFactorLoginKind {
+        match self.login_kind.as_str() {
+            "cookie" => TwoFactorLoginKind::Cookie,
+            _ => TwoFactorLoginKind::Token,
+        }
+    }
+
+    /// Not used yet and not expired
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.consumed_at.is_none() && self.expires_at > now
+    }
+}
+
+/// A code from the authenticator app, or a backup code
+#[derive(Debug, Clone, Default)]
+pub struct TwoFactorProof {
+    pub code: Option<String>,
+    pub backup_code: Option<String>,
+}
+
+/// Two-factor state of an account
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Enrollment started but not confirmed with a code
+    pub pending: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub backup_codes_remaining: i64,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// What an authenticator app needs to add the account
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct TwoFactorSetup {
+    /// Base32 secret, for typing in by hand
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code
+    pub provisioning_uri: String,
+}
+
+/// SHA-256 of a challenge token or normalized backup code, as stored
+pub fn hash_two_factor_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_kind() {
+        let now = Utc::now();
+        let challenge = TwoFactorChallenge {
+            id: Uuid::new_v4(),
+            customer_id: Uuid::new_v4(),
+            token_hash: hash_two_factor_token("tfa_abc"),
+            login_kind: TwoFactorLoginKind::Cookie.as_str().to_string(),
+            user_agent: None,
+            ip_address: None,
+            attempts: 0,
+            expires_at: now + chrono::Duration::minutes(5),
+            consumed_at: None,
+            created_at: now,
+        };
+        assert_eq!(challenge.kind(), TwoFactorLoginKind::Cookie);
+        assert!(challenge.is_open(now));
+        assert!(!challenge.is_open(now + chrono::Duration::minutes(6)));
+    }
+}
@@ -0,0 +1,119 @@
+//! Webhook replay models
+//!
+//! A replay re-sends an endpoint's outbound webhook events from a time range,
+//! e.g. after the consumer was down. Replayed payloads carry a `replay` marker
+//! so consumers can detect duplicates.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Replay status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_replay_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookReplayStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl WebhookReplayStatus {
+    /// Whether the replay has stopped for good
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A webhook replay and its progress
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookReplay {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    /// Event types replayed (empty = all)
+    pub event_types: Vec<String>,
+    pub from_time: DateTime<Utc>,
+    pub to_time: DateTime<Utc>,
+    /// Only events the endpoint never acknowledged with a 2xx
+    pub failed_only: bool,
+    pub rate_per_second: i32,
+    pub status: WebhookReplayStatus,
+    pub total_events: i32,
+    pub sent: i32,
+    pub succeeded: i32,
+    pub failed: i32,
+    #[serde(skip)]
+    pub cursor_created_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub cursor_id: Option<Uuid>,
+    pub error_message: Option<String>,
+    pub requested_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Request to replay an endpoint's events
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CreateWebhookReplayRequest {
+    /// Event types to replay; all when empty
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Skip events the endpoint already acknowledged
+    #[serde(default)]
+    pub failed_only: bool,
+    /// Deliveries per second (defaults to `webhook_replay.default_rate_per_second`)
+    #[validate(range(min = 1))]
+    pub rate_per_second: Option<u32>,
+    /// Only count the events that would be replayed
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Marker added to replayed payloads (`replay`)
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReplayMarker {
+    pub replay_id: Uuid,
+    pub original_delivery_id: Uuid,
+    pub original_sent_at: DateTime<Utc>,
+    /// Always true; consumers should deduplicate by `original_delivery_id`
+    pub redelivery: bool,
+}
+
+/// An original delivery selected for replay
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReplayableEvent {
+    /// Original delivery ID
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReplayableEvent {
+    /// Payload with a duplicate-delivery marker. Object payloads get a
+    /// `replay` key; others are wrapped as `{"data": ..., "replay": ...}`.
+    pub fn replay_payload(&self, replay_id: Uuid) -> serde_json::Value {
+        let marker = serde_json::to_value(ReplayMarker {
+            replay_id,
+            original_delivery_id: self.id,
+            original_sent_at: self.created_at,
+            redelivery: true,
+        })
+        .unwrap_or_default();
+
+        match self.payload.clone() {
+            serde_json::Value::Object(mut payload) => {
+                payload.insert("replay".to_string(), marker);
+                serde_json::Value::Object(payload)
+            }
+            other => serde_json::json!({ "data": other, "replay": marker }),
+        }
+    }
+}
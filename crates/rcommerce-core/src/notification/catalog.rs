@@ -0,0 +1,481 @@
+//! Template variables catalog
+//!
+//! Lists the placeholders each email template type receives, with sample
+//! values taken from the same mock data `rcommerce email test` renders, and
+//! validates merchant-edited templates against that list before they are
+//! saved.
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::config::EmailBranding;
+use crate::notification::email_templates::{Address, EmailNotificationFactory, OrderConfirmationParams, OrderItem};
+use crate::notification::{theme, TemplateVariables};
+use crate::services::FormattingService;
+use crate::{Error, Result};
+
+/// An email template type
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TemplateType {
+    /// Type name used by the CLI and for DB-stored templates
+    pub id: &'static str,
+    /// Built-in template loaded by `NotificationTemplate::load`
+    pub template_id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Variables an override must use, or the email is useless
+    pub required: &'static [&'static str],
+}
+
+/// All email template types
+pub const TEMPLATE_TYPES: &[TemplateType] = &[
+    TemplateType { id: "order_confirmation", template_id: "order_confirmation_html", name: "Order Confirmation", description: "Sent when a new order is placed", required: &["order_number"] },
+    TemplateType { id: "order_shipped", template_id: "order_shipped_html", name: "Order Shipped", description: "Sent when an order is shipped", required: &["order_number", "tracking_number"] },
+    TemplateType { id: "order_cancelled", template_id: "order_cancelled_html", name: "Order Cancelled", description: "Sent when an order is cancelled", required: &["order_number"] },
+    TemplateType { id: "payment_successful", template_id: "payment_successful_html", name: "Payment Successful", description: "Sent when payment is confirmed", required: &["order_number", "amount"] },
+    TemplateType { id: "payment_failed", template_id: "payment_failed_html", name: "Payment Failed", description: "Sent when payment fails", required: &["order_number", "retry_url"] },
+    TemplateType { id: "refund_processed", template_id: "refund_processed_html", name: "Refund Processed", description: "Sent when a refund is processed", required: &["order_number", "refund_amount"] },
+    TemplateType { id: "return_approved", template_id: "return_approved_html", name: "Return Approved", description: "Sent when a return request is approved", required: &["rma_number", "return_instructions"] },
+    TemplateType { id: "return_rejected", template_id: "return_rejected_html", name: "Return Rejected", description: "Sent when a return request is rejected", required: &["rma_number"] },
+    TemplateType { id: "subscription_created", template_id: "subscription_created_html", name: "Subscription Created", description: "Sent when a subscription is created", required: &["plan_name"] },
+    TemplateType { id: "subscription_renewal", template_id: "subscription_renewal_html", name: "Subscription Renewal", description: "Sent when a subscription renews", required: &["plan_name", "amount"] },
+    TemplateType { id: "subscription_cancelled", template_id: "subscription_cancelled_html", name: "Subscription Cancelled", description: "Sent when a subscription is cancelled", required: &["plan_name"] },
+    TemplateType { id: "dunning_first", template_id: "dunning_first_html", name: "Dunning: First Notice", description: "First payment failure notice", required: &["order_number", "retry_url"] },
+    TemplateType { id: "dunning_retry", template_id: "dunning_retry_html", name: "Dunning: Retry Notice", description: "Subsequent payment retry notice", required: &["order_number", "update_payment_url"] },
+    TemplateType { id: "dunning_final", template_id: "dunning_final_html", name: "Dunning: Final Notice", description: "Final notice before cancellation", required: &["order_number", "update_payment_url"] },
+    TemplateType { id: "welcome", template_id: "welcome_html", name: "Welcome", description: "Sent to new customers", required: &["login_url"] },
+    TemplateType { id: "password_reset", template_id: "password_reset_html", name: "Password Reset", description: "Sent for password reset requests", required: &["reset_url"] },
+    TemplateType { id: "abandoned_cart", template_id: "abandoned_cart_html", name: "Abandoned Cart", description: "Sent for abandoned cart reminders", required: &["cart_url"] },
+];
+
+/// Descriptions of the variables templates receive
+const VARIABLE_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("access_until", "Date the customer keeps access until"),
+    ("amount", "Amount charged, formatted with currency"),
+    ("attempt_number", "Current payment retry attempt"),
+    ("billing_address", "Billing address as HTML"),
+    ("billing_city", "Billing city, state and postal code"),
+    ("billing_company", "Billing name or company"),
+    ("billing_country", "Billing country"),
+    ("billing_street", "Billing street address"),
+    ("billing_date", "Date the renewal was billed"),
+    ("brand_color", "Accent color from the email branding"),
+    ("cancellation_date", "Date the cancellation takes effect"),
+    ("cancellation_reason", "Why the order was cancelled"),
+    ("cart_items", "Summary of the items left in the cart"),
+    ("cart_total", "Cart total, formatted with currency"),
+    ("cart_url", "Link back to the cart"),
+    ("company_name", "Store name"),
+    ("contact_support_url", "Link to the support page"),
+    ("customer_name", "Customer's full name"),
+    ("decision_notes", "Staff notes on the return decision"),
+    ("discount_code", "Discount code offered to the customer"),
+    ("end_date", "Date the subscription ends"),
+    ("error_message", "Reason the payment failed"),
+    ("estimated_delivery", "Estimated delivery date"),
+    ("expires_in", "How long the reset link is valid"),
+    ("final_date", "Last date to update payment details"),
+    ("footer_text", "Extra footer line from the email branding (may be empty)"),
+    ("help_center_url", "Link to the help center"),
+    ("interval", "Billing interval"),
+    ("invoice_url", "Link to the invoice"),
+    ("items", "Order items as an HTML table"),
+    ("login_url", "Link to the login page"),
+    ("logo_url", "Logo image from the email branding (may be empty)"),
+    ("max_attempts", "Maximum number of payment retries"),
+    ("next_billing_date", "Date of the next charge"),
+    ("next_retry_date", "Date of the next payment retry"),
+    ("order_date", "Date the order was placed"),
+    ("order_number", "Order number"),
+    ("order_total", "Order total, formatted with currency"),
+    ("payment_date", "Date the payment was taken"),
+    ("payment_method", "Payment method description"),
+    ("plan_name", "Subscription plan name"),
+    ("processing_time", "How long the refund takes to arrive"),
+    ("reason", "Cancellation reason"),
+    ("refund_amount", "Refunded amount, formatted with currency"),
+    ("refund_method", "Where the refund is sent"),
+    ("reset_token", "Password reset code"),
+    ("reset_url", "Password reset link"),
+    ("retry_url", "Link to retry the payment"),
+    ("return_instructions", "How to send the items back, with the label link"),
+    ("rma_number", "Return authorization number"),
+    ("shipping_address", "Shipping address as HTML"),
+    ("shipping_carrier", "Shipping carrier name"),
+    ("shipping_city_state_zip", "Shipping city, state and postal code"),
+    ("shipping_country", "Shipping country"),
+    ("shipping_street", "Shipping street address"),
+    ("shop_url", "Link to the storefront"),
+    ("subscription_id", "Subscription reference"),
+    ("support_email", "Support email address"),
+    ("tracking_number", "Shipment tracking number"),
+    ("tracking_url", "Shipment tracking link"),
+    ("trial_end_date", "Date the free trial ends"),
+    ("update_payment_url", "Link to update payment details"),
+];
+
+/// A variable available to a template
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateVariableInfo {
+    pub name: String,
+    pub description: Option<&'static str>,
+    pub sample: String,
+}
+
+/// Variables available to one template type
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateCatalogEntry {
+    #[serde(flatten)]
+    pub template_type: TemplateType,
+    pub variables: Vec<TemplateVariableInfo>,
+}
+
+/// Look up a template type by id
+pub fn template_type(id: &str) -> Option<&'static TemplateType> {
+    TEMPLATE_TYPES.iter().find(|t| t.id == id)
+}
+
+/// Format a sample price (in cents) the way real emails are formatted
+fn sample_price(cents: i64) -> String {
+    FormattingService::default().format_price(Decimal::new(cents, 2), "USD", None)
+}
+
+/// Mock variables for a template type, as used by `rcommerce email test`
+pub fn sample_variables(template_type: &str) -> Option<TemplateVariables> {
+    let mut vars = TemplateVariables::new();
+
+    match template_type {
+        "order_confirmation" => {
+            let items = vec![
+                OrderItem {
+                    name: "High-Performance Server Blade".to_string(),
+                    sku: "SRV-BLD-01".to_string(),
+                    quantity: 2,
+                    price: sample_price(320000),
+                },
+                OrderItem {
+                    name: "Enterprise Rust Support".to_string(),
+                    sku: "LIC-ENT-YR".to_string(),
+                    quantity: 1,
+                    price: sample_price(85000),
+                },
+            ];
+            let shipping = Address {
+                name: "John Doe".to_string(),
+                street: "123 Main Street, Suite 100".to_string(),
+                city: "San Francisco".to_string(),
+                state: "CA".to_string(),
+                zip: "94102".to_string(),
+                country: "United States".to_string(),
+            };
+            let billing = Address {
+                name: "John Doe".to_string(),
+                street: "456 Business Ave".to_string(),
+                city: "New York".to_string(),
+                state: "NY".to_string(),
+                zip: "10001".to_string(),
+                country: "United States".to_string(),
+            };
+
+            vars = EmailNotificationFactory::order_confirmation_variables(OrderConfirmationParams {
+                recipient_email: "test@example.com",
+                customer_name: "John Doe",
+                order_number: "ORD-2026-001234",
+                order_date: "Feb 5, 2026",
+                order_total: &sample_price(725000),
+                items: &items,
+                shipping_address: &shipping,
+                billing_address: &billing,
+            });
+        }
+        "order_shipped" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("order_date", "Feb 5, 2026");
+            vars.insert("tracking_number", "1Z999AA10123456784");
+            vars.insert("tracking_url", "https://tracking.example.com/1Z999AA10123456784");
+            vars.insert("shipping_carrier", "UPS");
+            vars.insert("estimated_delivery", "Feb 8, 2026");
+        }
+        "order_cancelled" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("order_date", "Feb 5, 2026");
+            vars.insert("cancellation_reason", "Customer requested cancellation");
+            vars.insert("refund_amount", sample_price(725000));
+        }
+        "payment_successful" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("amount", sample_price(725000));
+            vars.insert("payment_method", "Visa ending in 4242");
+            vars.insert("payment_date", "Feb 5, 2026");
+        }
+        "payment_failed" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("amount", sample_price(725000));
+            vars.insert("error_message", "Your card was declined. Please try a different payment method.");
+            vars.insert("retry_url", "https://rcommerce.local/payment/retry/ORD-2026-001234");
+        }
+        "refund_processed" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("refund_amount", sample_price(725000));
+            vars.insert("refund_method", "Original payment method (Visa ending in 4242)");
+            vars.insert("processing_time", "5-7 business days");
+        }
+        "return_approved" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("rma_number", "RMA-1001");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("refund_amount", sample_price(320000));
+            vars.insert(
+                "return_instructions",
+                "Print your prepaid UPS label (https://rcommerce.local/labels/RMA-1001.pdf), pack the items securely and drop the parcel off at any UPS location.",
+            );
+            vars.insert("tracking_number", "1Z999AA10123456785");
+            vars.insert("decision_notes", "Thanks for sending the blade back unopened.");
+        }
+        "return_rejected" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("rma_number", "RMA-1001");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("decision_notes", "Support licenses cannot be returned once activated.");
+        }
+        "subscription_created" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("subscription_id", "SUB-2026-001");
+            vars.insert("plan_name", "Enterprise Plan");
+            vars.insert("amount", sample_price(29900));
+            vars.insert("interval", "Monthly");
+            vars.insert("next_billing_date", "Mar 5, 2026");
+            vars.insert("trial_end_date", "Feb 12, 2026");
+        }
+        "subscription_renewal" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("plan_name", "Enterprise Plan");
+            vars.insert("amount", sample_price(29900));
+            vars.insert("billing_date", "Feb 5, 2026");
+            vars.insert("next_billing_date", "Mar 5, 2026");
+            vars.insert("invoice_url", "https://rcommerce.local/invoices/INV-2026-001");
+        }
+        "subscription_cancelled" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("plan_name", "Enterprise Plan");
+            vars.insert("cancellation_date", "Feb 5, 2026");
+            vars.insert("end_date", "Mar 5, 2026");
+            vars.insert("access_until", "Mar 5, 2026");
+            vars.insert("reason", "Customer requested cancellation");
+        }
+        "dunning_first" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("amount", sample_price(29900));
+            vars.insert("payment_method", "Visa ending in 4242");
+            vars.insert("error_message", "Your card was declined.");
+            vars.insert("retry_url", "https://rcommerce.local/payment/update");
+        }
+        "dunning_retry" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("amount", sample_price(29900));
+            vars.insert("attempt_number", "2");
+            vars.insert("max_attempts", "4");
+            vars.insert("next_retry_date", "Feb 7, 2026");
+            vars.insert("update_payment_url", "https://rcommerce.local/payment/update");
+        }
+        "dunning_final" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("order_number", "ORD-2026-001234");
+            vars.insert("amount", sample_price(29900));
+            vars.insert("final_date", "Feb 10, 2026");
+            vars.insert("cancellation_date", "Feb 12, 2026");
+            vars.insert("update_payment_url", "https://rcommerce.local/payment/update");
+            vars.insert("contact_support_url", "https://rcommerce.local/support");
+        }
+        "welcome" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("login_url", "https://rcommerce.local/login");
+            vars.insert("shop_url", "https://rcommerce.local/shop");
+            vars.insert("help_center_url", "https://rcommerce.local/help");
+        }
+        "password_reset" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("reset_url", "https://rcommerce.local/reset-password?token=abc123xyz");
+            vars.insert("reset_token", "ABC123XYZ789");
+            vars.insert("expires_in", "24 hours");
+        }
+        "abandoned_cart" => {
+            vars.insert("customer_name", "John Doe");
+            vars.insert("cart_items", "High-Performance Server Blade (x2), Enterprise Rust Support (x1)");
+            vars.insert("cart_total", sample_price(725000));
+            vars.insert("cart_url", "https://rcommerce.local/cart");
+            vars.insert("discount_code", "COMEBACK10");
+        }
+        _ => return None,
+    }
+
+    for (name, value) in EmailBranding::default().variables() {
+        vars.insert(name, value);
+    }
+    Some(vars)
+}
+
+/// Catalog entry for one template type
+pub fn catalog_entry(id: &str) -> Option<TemplateCatalogEntry> {
+    let template_type = *template_type(id)?;
+    let samples = sample_variables(id)?;
+
+    let mut variables: Vec<TemplateVariableInfo> = samples
+        .iter()
+        .map(|(name, sample)| TemplateVariableInfo {
+            name: name.clone(),
+            description: VARIABLE_DESCRIPTIONS
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, d)| *d),
+            sample: sample.clone(),
+        })
+        .collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Some(TemplateCatalogEntry { template_type, variables })
+}
+
+/// Catalog for every template type
+pub fn catalog() -> Vec<TemplateCatalogEntry> {
+    TEMPLATE_TYPES.iter().filter_map(|t| catalog_entry(t.id)).collect()
+}
+
+/// Placeholder names used in a template, accepting `{{ name }}` and `{{name}}`
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let tag = after[..end].trim();
+        // Block helpers: `{{#if name}}` refers to `name`, closing tags and `else` to nothing
+        let name = match tag.strip_prefix('#') {
+            Some(block) => block.split_whitespace().last().unwrap_or_default(),
+            None if tag.starts_with('/') || tag == "else" => "",
+            None => tag,
+        };
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+
+    names
+}
+
+/// Required variables of a template type that none of `parts` use
+pub fn missing_required(template_type: &TemplateType, parts: &[&str]) -> Vec<&'static str> {
+    let used: Vec<String> = parts.iter().flat_map(|part| placeholders(part)).collect();
+    template_type
+        .required
+        .iter()
+        .copied()
+        .filter(|name| !used.iter().any(|u| u == name))
+        .collect()
+}
+
+/// Check that a template only uses placeholders its type provides; `parts`
+/// are the subject, text and HTML templates. The email layout is checked
+/// as a layout.
+pub fn validate_template(template_type: &str, parts: &[&str]) -> Result<()> {
+    if template_type == theme::LAYOUT {
+        return theme::check_layout(parts.get(2).copied().unwrap_or_default());
+    }
+
+    let entry = catalog_entry(template_type).ok_or_else(|| {
+        Error::validation(format!(
+            "Unknown template type '{}'. Expected one of: {}",
+            template_type,
+            TEMPLATE_TYPES.iter().map(|t| t.id).collect::<Vec<_>>().join(", ")
+        ))
+    })?;
+
+    let mut unknown: Vec<String> = parts
+        .iter()
+        .flat_map(|part| placeholders(part))
+        .filter(|name| !entry.variables.iter().any(|v| &v.name == name))
+        .collect();
+    unknown.dedup();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::validation(format!(
+            "Unknown template variables for '{}': {}",
+            template_type,
+            unknown.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::NotificationTemplate;
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("Hi {{ customer_name }}, order {{order_number}} {{ customer_name }} {{"),
+            vec!["customer_name".to_string(), "order_number".to_string()]
+        );
+        assert_eq!(
+            placeholders("{{#if trial_end_date}}Trial ends {{ trial_end_date }}{{else}}-{{/if}}"),
+            vec!["trial_end_date".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_catalog_covers_builtin_templates() {
+        for template_type in TEMPLATE_TYPES {
+            let entry = catalog_entry(template_type.id).expect("every type has sample data");
+            assert!(entry.variables.iter().all(|v| v.description.is_some()), "{} has undocumented variables", template_type.id);
+
+            // Every placeholder in the built-in HTML must be in the catalog
+            let template = NotificationTemplate::builtin(template_type.template_id).unwrap();
+            let html = template.html_body.clone().unwrap_or_default();
+            for name in placeholders(&html) {
+                assert!(
+                    entry.variables.iter().any(|v| v.name == name),
+                    "{} uses {{{{ {} }}}} which is not in the catalog",
+                    template_type.id,
+                    name
+                );
+            }
+            assert!(
+                missing_required(template_type, &[&template.subject, &template.body, &html]).is_empty(),
+                "{} lacks a required variable",
+                template_type.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("password_reset", &["Reset: {{ reset_url }}"]).is_ok());
+
+        let err = validate_template("password_reset", &["{{reset_link}}", "{{ customer_name }}"]).unwrap_err();
+        assert!(err.to_string().contains("reset_link"));
+
+        assert!(validate_template("shipping_notification", &[""]).is_err());
+
+        assert!(validate_template("layout", &["", "", "<main>{{ content }}</main>{{ footer_text }}"]).is_ok());
+        assert!(validate_template("layout", &["", "", "<main>{{ order_number }}</main>"]).is_err());
+    }
+
+    #[test]
+    fn test_missing_required() {
+        let reset = template_type("password_reset").unwrap();
+        assert_eq!(missing_required(reset, &["Reset your password", "{{ expires_in }}"]), vec!["reset_url"]);
+        assert!(missing_required(reset, &["", "<a href=\"{{reset_url}}\">Reset</a>"]).is_empty());
+    }
+}
@@ -5,9 +5,11 @@ use crate::notification::types::{NotificationMessage, NotificationResult};
 use crate::Result;
 
 pub mod email;
+pub mod sendgrid;
+pub mod ses;
 
 // Re-export EmailChannel from the email module
-pub use email::EmailChannel;
+pub use email::{EmailChannel, EmailEvent};
 
 /// Channel sender trait for sending notification messages
 #[async_trait]
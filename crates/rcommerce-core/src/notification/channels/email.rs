@@ -1,10 +1,20 @@
 //! Email notification channel implementation
 //!
-//! This module provides email sending capabilities using SMTP.
-//! It supports both real SMTP servers and a mock mode for testing.
+//! This module provides email sending capabilities using SMTP, SendGrid
+//! or Amazon SES, selected with `notifications.email.provider`.
+//! It also supports a mock mode for testing.
+
+use async_trait::async_trait;
+use uuid::Uuid;
 
 use crate::{Result, Error};
-use crate::notification::{Notification, NotificationChannel};
+use crate::config::{EmailConfig, EmailProvider};
+use crate::notification::{DeliveryStatus, Notification, NotificationChannel};
+use crate::notification::channels::ChannelSender;
+use crate::notification::channels::sendgrid::SendGridSender;
+use crate::notification::channels::ses::SesSender;
+use crate::notification::preferences::unsubscribe_headers;
+use crate::notification::types::{NotificationMessage, NotificationResult};
 use lettre::{
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
     message::{header, MultiPart, SinglePart},
@@ -16,6 +26,10 @@ use lettre::{
 pub enum EmailMode {
     /// Send emails via SMTP
     Smtp(SmtpConfig),
+    /// Send emails via the SendGrid Web API
+    SendGrid(SendGridSender),
+    /// Send emails via Amazon SES
+    Ses(SesSender),
     /// Log emails to console (for testing)
     Mock,
     /// Save emails to a directory (for testing)
@@ -80,6 +94,65 @@ impl EmailChannel {
         })
     }
     
+    /// Create an email channel sending through SendGrid
+    pub fn new_sendgrid(sender: SendGridSender) -> Self {
+        Self {
+            mode: EmailMode::SendGrid(sender),
+            transport: None,
+        }
+    }
+    
+    /// Create an email channel sending through Amazon SES
+    pub fn new_ses(sender: SesSender) -> Self {
+        Self {
+            mode: EmailMode::Ses(sender),
+            transport: None,
+        }
+    }
+    
+    /// Create the email channel for the configured provider
+    pub async fn from_config(config: &EmailConfig) -> Result<Self> {
+        let from_address = config
+            .from_email
+            .clone()
+            .unwrap_or_else(|| "notifications@rcommerce.local".to_string());
+        let from_name = config.from_name.clone().unwrap_or_else(|| "R Commerce".to_string());
+        
+        match config.provider {
+            EmailProvider::Smtp => {
+                let host = config
+                    .smtp_host
+                    .clone()
+                    .ok_or_else(|| Error::config("notifications.email.smtp_host is not configured"))?;
+                Self::new_smtp(SmtpConfig {
+                    host,
+                    port: config.smtp_port.unwrap_or(587),
+                    username: config.smtp_user.clone().unwrap_or_default(),
+                    password: config.smtp_pass.clone().unwrap_or_default(),
+                    from_address,
+                    from_name,
+                    use_tls: config.smtp_tls,
+                })
+                .await
+            }
+            EmailProvider::SendGrid => {
+                let sendgrid = config
+                    .sendgrid
+                    .clone()
+                    .ok_or_else(|| Error::config("notifications.email.sendgrid is not configured"))?;
+                Ok(Self::new_sendgrid(SendGridSender::new(sendgrid, from_address, from_name)))
+            }
+            EmailProvider::Ses => {
+                let ses = config
+                    .ses
+                    .clone()
+                    .ok_or_else(|| Error::config("notifications.email.ses is not configured"))?;
+                Ok(Self::new_ses(SesSender::new(ses, from_address, from_name)?))
+            }
+            EmailProvider::Mock => Ok(Self::new_mock()),
+        }
+    }
+    
     /// Create a mock email channel for testing (logs to console)
     pub fn new_mock() -> Self {
         log::info!("Email channel created in MOCK mode - emails will be logged to console");
@@ -104,23 +177,50 @@ impl EmailChannel {
     
     /// Send an email notification
     pub async fn send(&self, notification: &Notification) -> Result<()> {
+        self.deliver(notification).await.map(|_| ())
+    }
+    
+    /// Send an email notification, returning the provider's message id
+    /// when it assigns one (SendGrid and SES)
+    pub async fn deliver(&self, notification: &Notification) -> Result<Option<String>> {
         if notification.channel != NotificationChannel::Email {
             return Err(Error::notification_error("Invalid channel for email sender"));
         }
         
         match &self.mode {
             EmailMode::Smtp(config) => {
-                self.send_smtp(notification, config).await
+                self.send_smtp(notification, config).await.map(|_| None)
+            }
+            EmailMode::SendGrid(sender) => {
+                let message_id = sender.send(notification).await?;
+                log::info!("Email sent via SendGrid to {}: message_id={:?}", notification.recipient, message_id);
+                Ok(message_id)
+            }
+            EmailMode::Ses(sender) => {
+                let message_id = sender.send(notification).await?;
+                log::info!("Email sent via SES to {}: message_id={:?}", notification.recipient, message_id);
+                Ok(message_id)
             }
             EmailMode::Mock => {
-                self.send_mock(notification).await
+                self.send_mock(notification).await.map(|_| None)
             }
             EmailMode::FileSystem { output_dir } => {
-                self.send_filesystem(notification, output_dir).await
+                self.send_filesystem(notification, output_dir).await.map(|_| None)
             }
         }
     }
     
+    /// Name of the provider emails are sent with
+    pub fn provider_name(&self) -> &'static str {
+        match self.mode {
+            EmailMode::Smtp(_) => "smtp",
+            EmailMode::SendGrid(_) => "sendgrid",
+            EmailMode::Ses(_) => "ses",
+            EmailMode::Mock => "mock",
+            EmailMode::FileSystem { .. } => "filesystem",
+        }
+    }
+    
     /// Send email via SMTP
     async fn send_smtp(&self, notification: &Notification, config: &SmtpConfig) -> Result<()> {
         let transport = self.transport.as_ref()
@@ -128,10 +228,13 @@ impl EmailChannel {
         
         let from = format!("{} <{}>", config.from_name, config.from_address);
         
-        let message_builder = Message::builder()
+        let mut message_builder = Message::builder()
             .from(from.parse().map_err(|e| Error::notification_error(format!("Invalid from address: {}", e)))?)
             .to(notification.recipient.parse().map_err(|e| Error::notification_error(format!("Invalid recipient: {}", e)))?)
             .subject(notification.subject.clone());
+        for (name, value) in unsubscribe_headers(notification) {
+            message_builder = message_builder.raw_header(header::HeaderValue::new(header::HeaderName::new_from_ascii_str(name), value));
+        }
         
         let message = if let Some(ref html_body) = notification.html_body {
             message_builder.multipart(
@@ -247,6 +350,50 @@ impl EmailChannel {
     }
 }
 
+#[async_trait]
+impl ChannelSender for EmailChannel {
+    async fn send(&self, message: &NotificationMessage) -> Result<NotificationResult> {
+        let notification = Notification::new(
+            NotificationChannel::Email,
+            message.recipient.clone(),
+            message.subject.clone().unwrap_or_default(),
+            message.body.clone(),
+        );
+        
+        Ok(match self.deliver(&notification).await {
+            Ok(message_id) => NotificationResult {
+                success: true,
+                channel: self.provider_name().to_string(),
+                message_id: message_id.or_else(|| Some(notification.id.to_string())),
+                error: None,
+            },
+            Err(e) => NotificationResult {
+                success: false,
+                channel: self.provider_name().to_string(),
+                message_id: None,
+                error: Some(e.to_string()),
+            },
+        })
+    }
+    
+    fn channel_name(&self) -> &'static str {
+        "email"
+    }
+}
+
+/// A delivery status change reported by an email provider's webhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailEvent {
+    /// Our notification id, when the provider echoed it back
+    pub notification_id: Option<Uuid>,
+    /// The provider's message id
+    pub message_id: Option<String>,
+    pub recipient: String,
+    pub status: DeliveryStatus,
+    /// Bounce diagnostic, complaint type or similar
+    pub detail: Option<String>,
+}
+
 /// Represents an email message with both plain text and HTML parts
 #[derive(Debug, Clone)]
 pub struct EmailMessage {
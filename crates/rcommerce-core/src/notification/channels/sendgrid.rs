@@ -0,0 +1,220 @@
+//! SendGrid email provider
+//!
+//! Sends through the v3 Mail Send API and reads the Event Webhook. Each
+//! message carries the notification id as a custom arg, which SendGrid
+//! echoes on every event for that message.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::config::SendGridConfig;
+use crate::notification::channels::email::EmailEvent;
+use crate::notification::preferences::unsubscribe_headers;
+use crate::notification::{DeliveryStatus, Notification};
+use crate::{Error, Result};
+
+/// Header carrying the Event Webhook signature
+pub const SIGNATURE_HEADER: &str = "x-twilio-email-event-webhook-signature";
+/// Header carrying the timestamp covered by the signature
+pub const TIMESTAMP_HEADER: &str = "x-twilio-email-event-webhook-timestamp";
+
+/// DER prefix of a P-256 SubjectPublicKeyInfo; the uncompressed point follows
+const P256_SPKI_PREFIX: [u8; 26] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+
+/// Sends email through the SendGrid Web API
+#[derive(Debug, Clone)]
+pub struct SendGridSender {
+    client: reqwest::Client,
+    config: SendGridConfig,
+    from_address: String,
+    from_name: String,
+}
+
+impl SendGridSender {
+    pub fn new(config: SendGridConfig, from_address: String, from_name: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+            from_address,
+            from_name,
+        }
+    }
+
+    /// Send a notification; returns SendGrid's message id
+    pub async fn send(&self, notification: &Notification) -> Result<Option<String>> {
+        let url = format!("{}/v3/mail/send", self.config.api_url.trim_end_matches('/'));
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.config.api_key)
+            .json(&self.payload(notification))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::notification_error(format!("SendGrid rejected the email ({}): {}", status, body)));
+        }
+
+        Ok(response
+            .headers()
+            .get("x-message-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string))
+    }
+
+    fn payload(&self, notification: &Notification) -> serde_json::Value {
+        let mut content = vec![json!({ "type": "text/plain", "value": notification.body })];
+        if let Some(ref html) = notification.html_body {
+            content.push(json!({ "type": "text/html", "value": html }));
+        }
+
+        let mut payload = json!({
+            "personalizations": [{
+                "to": [{ "email": notification.recipient }],
+                "custom_args": { "notification_id": notification.id.to_string() },
+            }],
+            "from": { "email": self.from_address, "name": self.from_name },
+            "subject": notification.subject,
+            "content": content,
+        });
+        let headers: serde_json::Map<String, serde_json::Value> = unsubscribe_headers(notification)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), json!(value)))
+            .collect();
+        if !headers.is_empty() {
+            payload["headers"] = serde_json::Value::Object(headers);
+        }
+        payload
+    }
+}
+
+/// Verify a signed Event Webhook request
+///
+/// `public_key` is the base64 key shown in the SendGrid settings; the
+/// signature covers the timestamp header followed by the raw body.
+pub fn verify_signature(public_key: &str, signature: &str, timestamp: &str, body: &[u8]) -> Result<()> {
+    let der = STANDARD
+        .decode(public_key.trim())
+        .map_err(|e| Error::config(format!("Invalid SendGrid webhook public key: {}", e)))?;
+    let point = der
+        .strip_prefix(&P256_SPKI_PREFIX[..])
+        .ok_or_else(|| Error::config("SendGrid webhook public key is not a P-256 key"))?;
+    let signature = STANDARD
+        .decode(signature.trim())
+        .map_err(|_| Error::unauthorized("Invalid SendGrid webhook signature"))?;
+
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(body);
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, point)
+        .verify(&message, &signature)
+        .map_err(|_| Error::unauthorized("Invalid SendGrid webhook signature"))
+}
+
+#[derive(Debug, Deserialize)]
+struct SendGridEvent {
+    event: String,
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    sg_message_id: Option<String>,
+    #[serde(default)]
+    notification_id: Option<String>,
+    #[serde(default, rename = "type")]
+    bounce_type: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Parse an Event Webhook body into delivery status changes
+///
+/// Events that don't change the delivery status (processed, deferred,
+/// opens, clicks, ...) are skipped.
+pub fn parse_events(body: &[u8]) -> Result<Vec<EmailEvent>> {
+    let events: Vec<SendGridEvent> = serde_json::from_slice(body)
+        .map_err(|e| Error::validation(format!("Invalid SendGrid event payload: {}", e)))?;
+
+    Ok(events
+        .into_iter()
+        .filter_map(|event| {
+            let status = match (event.event.as_str(), event.bounce_type.as_deref()) {
+                ("delivered", _) => DeliveryStatus::Delivered,
+                // Blocks are temporary refusals by the receiving server
+                ("bounce", Some("blocked")) => DeliveryStatus::Failed,
+                ("bounce", _) => DeliveryStatus::Bounced,
+                ("dropped", _) => DeliveryStatus::Failed,
+                ("spamreport", _) => DeliveryStatus::Complained,
+                _ => return None,
+            };
+            Some(EmailEvent {
+                notification_id: event.notification_id.and_then(|id| Uuid::parse_str(&id).ok()),
+                // "<X-Message-Id>.<filter>.<n>" - the prefix is what the API returned
+                message_id: event
+                    .sg_message_id
+                    .map(|id| id.split('.').next().unwrap_or_default().to_string()),
+                recipient: event.email,
+                status,
+                detail: event.reason.or(Some(event.event)),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+
+    #[test]
+    fn test_parse_events() {
+        let id = Uuid::new_v4();
+        let body = format!(
+            r#"[
+                {{"event": "processed", "email": "a@example.com", "sg_message_id": "abc.filter0001.1"}},
+                {{"event": "delivered", "email": "a@example.com", "sg_message_id": "abc.filter0001.1", "notification_id": "{id}"}},
+                {{"event": "bounce", "type": "bounce", "email": "b@example.com", "reason": "550 5.1.1 unknown user", "sg_message_id": "def.filter0001.2"}},
+                {{"event": "bounce", "type": "blocked", "email": "c@example.com"}},
+                {{"event": "spamreport", "email": "d@example.com", "notification_id": "{id}"}}
+            ]"#
+        );
+
+        let events = parse_events(body.as_bytes()).unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].status, DeliveryStatus::Delivered);
+        assert_eq!(events[0].notification_id, Some(id));
+        assert_eq!(events[0].message_id.as_deref(), Some("abc"));
+        assert_eq!(events[1].status, DeliveryStatus::Bounced);
+        assert_eq!(events[1].detail.as_deref(), Some("550 5.1.1 unknown user"));
+        assert_eq!(events[2].status, DeliveryStatus::Failed);
+        assert_eq!(events[3].status, DeliveryStatus::Complained);
+
+        assert!(parse_events(b"{}").is_err());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+        let mut spki = P256_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(key_pair.public_key().as_ref());
+        let public_key = STANDARD.encode(&spki);
+
+        let body = br#"[{"event":"delivered"}]"#;
+        let signature = key_pair.sign(&rng, b"1700000000[{\"event\":\"delivered\"}]").unwrap();
+        let signature = STANDARD.encode(signature.as_ref());
+
+        assert!(verify_signature(&public_key, &signature, "1700000000", body).is_ok());
+        assert!(verify_signature(&public_key, &signature, "1700000001", body).is_err());
+        assert!(verify_signature(&public_key, &signature, "1700000000", b"[]").is_err());
+        assert!(verify_signature("bm90IGEga2V5", &signature, "1700000000", body).is_err());
+    }
+}
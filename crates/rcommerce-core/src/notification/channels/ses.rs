@@ -0,0 +1,520 @@
+//! Amazon SES email provider
+//!
+//! Sends through the SES v2 API (requests signed with AWS Signature
+//! Version 4) and reads the bounce, complaint and delivery notifications
+//! SES publishes to an SNS topic. Each message is tagged with the
+//! notification id; notifications that don't carry tags are matched by
+//! SES message id instead.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::config::SesConfig;
+use crate::notification::channels::email::EmailEvent;
+use crate::notification::preferences::unsubscribe_headers;
+use crate::notification::{DeliveryStatus, Notification};
+use crate::{Error, Result};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Message tag carrying the notification id
+const NOTIFICATION_TAG: &str = "notification_id";
+
+/// Sends email through the Amazon SES v2 API
+#[derive(Debug, Clone)]
+pub struct SesSender {
+    client: reqwest::Client,
+    config: SesConfig,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    from: String,
+}
+
+impl SesSender {
+    /// Create a sender; credentials missing from the config are read from
+    /// the standard AWS environment variables
+    pub fn new(config: SesConfig, from_address: String, from_name: String) -> Result<Self> {
+        let from_env = |value: &Option<String>, var: &str| value.clone().or_else(|| std::env::var(var).ok());
+        let access_key_id = from_env(&config.access_key_id, "AWS_ACCESS_KEY_ID")
+            .ok_or_else(|| Error::config("SES access key id is not configured"))?;
+        let secret_access_key = from_env(&config.secret_access_key, "AWS_SECRET_ACCESS_KEY")
+            .ok_or_else(|| Error::config("SES secret access key is not configured"))?;
+        let session_token = from_env(&config.session_token, "AWS_SESSION_TOKEN");
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            access_key_id,
+            secret_access_key,
+            session_token,
+            from: format!("{} <{}>", from_name, from_address),
+            config,
+        })
+    }
+
+    fn host(&self) -> String {
+        match self.config.endpoint {
+            Some(ref endpoint) => endpoint
+                .trim_start_matches("https://")
+                .trim_end_matches('/')
+                .to_string(),
+            None => format!("email.{}.amazonaws.com", self.config.region),
+        }
+    }
+
+    /// Send a notification; returns the SES message id
+    pub async fn send(&self, notification: &Notification) -> Result<Option<String>> {
+        const PATH: &str = "/v2/email/outbound-emails";
+
+        let host = self.host();
+        let body = serde_json::to_vec(&self.payload(notification))?;
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut headers = vec![
+            ("content-type".to_string(), "application/json".to_string()),
+            ("host".to_string(), host.clone()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(ref token) = self.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        let authorization = sign_v4(
+            &SigningRequest {
+                method: "POST",
+                path: PATH,
+                query: "",
+                headers: &headers,
+                body: &body,
+            },
+            &Credentials {
+                access_key_id: &self.access_key_id,
+                secret_access_key: &self.secret_access_key,
+            },
+            &self.config.region,
+            "ses",
+            &amz_date,
+        );
+
+        let mut request = self
+            .client
+            .post(format!("https://{}{}", host, PATH))
+            .header("authorization", authorization)
+            .body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::notification_error(format!("SES rejected the email ({}): {}", status, body)));
+        }
+
+        #[derive(Deserialize)]
+        struct SendEmailResponse {
+            #[serde(rename = "MessageId")]
+            message_id: Option<String>,
+        }
+        let response: SendEmailResponse = response.json().await?;
+        Ok(response.message_id)
+    }
+
+    fn payload(&self, notification: &Notification) -> serde_json::Value {
+        let mut body = json!({ "Text": { "Data": notification.body, "Charset": "UTF-8" } });
+        if let Some(ref html) = notification.html_body {
+            body["Html"] = json!({ "Data": html, "Charset": "UTF-8" });
+        }
+
+        let mut payload = json!({
+            "FromEmailAddress": self.from,
+            "Destination": { "ToAddresses": [notification.recipient] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": notification.subject, "Charset": "UTF-8" },
+                    "Body": body,
+                }
+            },
+            "EmailTags": [{ "Name": NOTIFICATION_TAG, "Value": notification.id.to_string() }],
+        });
+        if let Some(ref configuration_set) = self.config.configuration_set {
+            payload["ConfigurationSetName"] = json!(configuration_set);
+        }
+        let headers: Vec<_> = unsubscribe_headers(notification)
+            .into_iter()
+            .map(|(name, value)| json!({ "Name": name, "Value": value }))
+            .collect();
+        if !headers.is_empty() {
+            payload["Content"]["Simple"]["Headers"] = json!(headers);
+        }
+        payload
+    }
+}
+
+/// AWS credentials used for signing
+pub struct Credentials<'a> {
+    pub access_key_id: &'a str,
+    pub secret_access_key: &'a str,
+}
+
+/// The parts of an HTTP request covered by a Signature Version 4 signature
+pub struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    /// Canonical (sorted, encoded) query string
+    pub query: &'a str,
+    /// Header names with their values; all are signed
+    pub headers: &'a [(String, String)],
+    pub body: &'a [u8],
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Derive the Signature Version 4 signing key for a day, region and service
+pub fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    hmac_sha256(&key, "aws4_request")
+}
+
+/// Build the `Authorization` header for a request (`amz_date` is the
+/// request's `x-amz-date`, e.g. `20150830T123600Z`)
+pub fn sign_v4(
+    request: &SigningRequest<'_>,
+    credentials: &Credentials<'_>,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+) -> String {
+    let mut headers: Vec<_> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(request.body))
+    );
+
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(credentials.secret_access_key, date, region, service);
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature
+    )
+}
+
+/// A message delivered by SNS to the webhook
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnsMessage {
+    /// A new subscription; visiting the URL confirms it
+    SubscriptionConfirmation { topic_arn: String, subscribe_url: String },
+    /// SES events carried by a notification
+    Notification(Vec<EmailEvent>),
+    /// Anything else (unsubscribe confirmations, ...)
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnsEnvelope {
+    #[serde(rename = "Type")]
+    kind: String,
+    #[serde(rename = "TopicArn", default)]
+    topic_arn: String,
+    #[serde(rename = "Message", default)]
+    message: String,
+    #[serde(rename = "SubscribeURL", default)]
+    subscribe_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesEvent {
+    // Identity notifications use notificationType, configuration set
+    // event publishing uses eventType
+    notification_type: Option<String>,
+    event_type: Option<String>,
+    mail: SesMail,
+    bounce: Option<SesBounce>,
+    complaint: Option<SesComplaint>,
+    delivery: Option<SesDelivery>,
+    reject: Option<SesReject>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesMail {
+    message_id: String,
+    #[serde(default)]
+    destination: Vec<String>,
+    #[serde(default)]
+    tags: std::collections::HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesBounce {
+    bounce_type: String,
+    #[serde(default)]
+    bounce_sub_type: String,
+    bounced_recipients: Vec<SesRecipient>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesRecipient {
+    email_address: String,
+    #[serde(default)]
+    diagnostic_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesComplaint {
+    complained_recipients: Vec<SesRecipient>,
+    #[serde(default)]
+    complaint_feedback_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesDelivery {
+    #[serde(default)]
+    recipients: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SesReject {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Parse the body of an SNS HTTP(S) delivery
+pub fn parse_sns(body: &[u8]) -> Result<SnsMessage> {
+    let envelope: SnsEnvelope = serde_json::from_slice(body)
+        .map_err(|e| Error::validation(format!("Invalid SNS message: {}", e)))?;
+
+    match envelope.kind.as_str() {
+        "SubscriptionConfirmation" => Ok(SnsMessage::SubscriptionConfirmation {
+            topic_arn: envelope.topic_arn,
+            subscribe_url: envelope
+                .subscribe_url
+                .ok_or_else(|| Error::validation("SNS subscription confirmation without SubscribeURL"))?,
+        }),
+        "Notification" => {
+            let event: SesEvent = serde_json::from_str(&envelope.message)
+                .map_err(|e| Error::validation(format!("Invalid SES notification: {}", e)))?;
+            Ok(SnsMessage::Notification(ses_events(event)))
+        }
+        _ => Ok(SnsMessage::Other),
+    }
+}
+
+fn ses_events(event: SesEvent) -> Vec<EmailEvent> {
+    let notification_id = event
+        .mail
+        .tags
+        .get(NOTIFICATION_TAG)
+        .and_then(|values| values.first())
+        .and_then(|id| Uuid::parse_str(id).ok());
+    let to_event = |recipient: String, status: DeliveryStatus, detail: Option<String>| EmailEvent {
+        notification_id,
+        message_id: Some(event.mail.message_id.clone()),
+        recipient,
+        status,
+        detail,
+    };
+
+    let kind = event.event_type.as_deref().or(event.notification_type.as_deref()).unwrap_or_default();
+    match kind {
+        "Bounce" => {
+            let Some(bounce) = event.bounce else { return Vec::new() };
+            // Transient bounces may still be delivered on a later attempt
+            let status = if bounce.bounce_type == "Permanent" {
+                DeliveryStatus::Bounced
+            } else {
+                DeliveryStatus::Failed
+            };
+            bounce
+                .bounced_recipients
+                .into_iter()
+                .map(|r| {
+                    let detail = r
+                        .diagnostic_code
+                        .unwrap_or_else(|| format!("{} bounce ({})", bounce.bounce_type, bounce.bounce_sub_type));
+                    to_event(r.email_address, status, Some(detail))
+                })
+                .collect()
+        }
+        "Complaint" => {
+            let Some(complaint) = event.complaint else { return Vec::new() };
+            let detail = complaint.complaint_feedback_type.or_else(|| Some("complaint".to_string()));
+            complaint
+                .complained_recipients
+                .into_iter()
+                .map(|r| to_event(r.email_address, DeliveryStatus::Complained, detail.clone()))
+                .collect()
+        }
+        "Delivery" => event
+            .delivery
+            .map(|d| d.recipients)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|recipient| to_event(recipient, DeliveryStatus::Delivered, None))
+            .collect(),
+        "Reject" => {
+            let detail = event.reject.and_then(|r| r.reason);
+            event
+                .mail
+                .destination
+                .iter()
+                .map(|recipient| to_event(recipient.clone(), DeliveryStatus::Failed, detail.clone()))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a SubscribeURL points at SNS, so it is safe to visit
+pub fn is_sns_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| host.starts_with("sns.") && host.ends_with(".amazonaws.com"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_v4() {
+        // Example request from the AWS Signature Version 4 documentation
+        let secret = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+        assert_eq!(
+            hex::encode(signing_key(secret, "20150830", "us-east-1", "iam")),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+
+        let headers = vec![
+            ("Content-Type".to_string(), "application/x-www-form-urlencoded; charset=utf-8".to_string()),
+            ("Host".to_string(), "iam.amazonaws.com".to_string()),
+            ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let authorization = sign_v4(
+            &SigningRequest {
+                method: "GET",
+                path: "/",
+                query: "Action=ListUsers&Version=2010-05-08",
+                headers: &headers,
+                body: b"",
+            },
+            &Credentials { access_key_id: "AKIDEXAMPLE", secret_access_key: secret },
+            "us-east-1",
+            "iam",
+            "20150830T123600Z",
+        );
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    fn sns_notification(message: serde_json::Value) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "Type": "Notification",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:ses-events",
+            "Message": message.to_string(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_sns() {
+        let id = Uuid::new_v4();
+        let bounce = sns_notification(json!({
+            "eventType": "Bounce",
+            "mail": { "messageId": "0100abc", "destination": ["a@example.com"], "tags": { "notification_id": [id.to_string()] } },
+            "bounce": {
+                "bounceType": "Permanent",
+                "bounceSubType": "General",
+                "bouncedRecipients": [{ "emailAddress": "a@example.com", "diagnosticCode": "smtp; 550 5.1.1 user unknown" }]
+            }
+        }));
+        let SnsMessage::Notification(events) = parse_sns(&bounce).unwrap() else { panic!("Expected notification") };
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, DeliveryStatus::Bounced);
+        assert_eq!(events[0].notification_id, Some(id));
+        assert_eq!(events[0].message_id.as_deref(), Some("0100abc"));
+        assert_eq!(events[0].detail.as_deref(), Some("smtp; 550 5.1.1 user unknown"));
+
+        // Identity notifications carry no tags
+        let complaint = sns_notification(json!({
+            "notificationType": "Complaint",
+            "mail": { "messageId": "0100def" },
+            "complaint": { "complainedRecipients": [{ "emailAddress": "b@example.com" }], "complaintFeedbackType": "abuse" }
+        }));
+        let SnsMessage::Notification(events) = parse_sns(&complaint).unwrap() else { panic!("Expected notification") };
+        assert_eq!(events[0].status, DeliveryStatus::Complained);
+        assert_eq!(events[0].notification_id, None);
+        assert_eq!(events[0].recipient, "b@example.com");
+
+        let transient = sns_notification(json!({
+            "notificationType": "Bounce",
+            "mail": { "messageId": "0100ghi" },
+            "bounce": { "bounceType": "Transient", "bouncedRecipients": [{ "emailAddress": "c@example.com" }] }
+        }));
+        let SnsMessage::Notification(events) = parse_sns(&transient).unwrap() else { panic!("Expected notification") };
+        assert_eq!(events[0].status, DeliveryStatus::Failed);
+
+        let confirmation = json!({
+            "Type": "SubscriptionConfirmation",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:ses-events",
+            "SubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=ConfirmSubscription&Token=abc",
+            "Message": "You have chosen to subscribe",
+        });
+        assert!(matches!(
+            parse_sns(confirmation.to_string().as_bytes()).unwrap(),
+            SnsMessage::SubscriptionConfirmation { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_sns_url() {
+        assert!(is_sns_url("https://sns.eu-west-1.amazonaws.com/?Action=ConfirmSubscription"));
+        assert!(!is_sns_url("http://sns.eu-west-1.amazonaws.com/"));
+        assert!(!is_sns_url("https://sns.eu-west-1.amazonaws.com.example.com/"));
+        assert!(!is_sns_url("https://example.com/sns.amazonaws.com"));
+    }
+}
@@ -16,6 +16,9 @@ pub enum EmailTemplateType {
     OrderShipped,
     PaymentFailed,
     PaymentSuccessful,
+    RefundProcessed,
+    ReturnApproved,
+    ReturnRejected,
     SubscriptionCreated,
     SubscriptionRenewal,
     SubscriptionCancelled,
@@ -34,6 +37,9 @@ impl EmailTemplateType {
             EmailTemplateType::OrderShipped => "order_shipped_html",
             EmailTemplateType::PaymentFailed => "payment_failed_html",
             EmailTemplateType::PaymentSuccessful => "payment_successful_html",
+            EmailTemplateType::RefundProcessed => "refund_processed_html",
+            EmailTemplateType::ReturnApproved => "return_approved_html",
+            EmailTemplateType::ReturnRejected => "return_rejected_html",
             EmailTemplateType::SubscriptionCreated => "subscription_created_html",
             EmailTemplateType::SubscriptionRenewal => "subscription_renewal_html",
             EmailTemplateType::SubscriptionCancelled => "subscription_cancelled_html",
@@ -58,7 +64,7 @@ pub struct OrderConfirmationParams<'a> {
     pub order_number: &'a str,
     /// Order date
     pub order_date: &'a str,
-    /// Order total
+    /// Order total, formatted for display (see `FormattingService`)
     pub order_total: &'a str,
     /// Order items
     pub items: &'a [OrderItem],
@@ -75,7 +81,14 @@ impl EmailNotificationFactory {
     /// Create an order confirmation email
     pub fn order_confirmation(params: OrderConfirmationParams<'_>) -> Result<Notification> {
         let template = NotificationTemplate::load("order_confirmation_html")?;
+        let recipient_email = params.recipient_email;
+        let vars = Self::order_confirmation_variables(params);
         
+        Self::create_notification(recipient_email, &template, vars)
+    }
+    
+    /// Variables for the order confirmation template
+    pub fn order_confirmation_variables(params: OrderConfirmationParams<'_>) -> TemplateVariables {
         let mut vars = TemplateVariables::new();
         vars.insert("customer_name", params.customer_name);
         vars.insert("order_number", params.order_number);
@@ -91,8 +104,19 @@ impl EmailNotificationFactory {
         // Format addresses
         vars.insert("shipping_address", Self::format_address(params.shipping_address));
         vars.insert("billing_address", Self::format_address(params.billing_address));
-        
-        Self::create_notification(params.recipient_email, &template, vars)
+
+        // Individual address lines used by the invoice layout
+        let shipping = params.shipping_address;
+        vars.insert("shipping_street", &shipping.street);
+        vars.insert("shipping_city_state_zip", format!("{}, {} {}", shipping.city, shipping.state, shipping.zip));
+        vars.insert("shipping_country", &shipping.country);
+        let billing = params.billing_address;
+        vars.insert("billing_company", &billing.name);
+        vars.insert("billing_street", &billing.street);
+        vars.insert("billing_city", format!("{}, {} {}", billing.city, billing.state, billing.zip));
+        vars.insert("billing_country", &billing.country);
+
+        vars
     }
     
     /// Create a payment failed email (dunning)
@@ -156,6 +180,78 @@ impl EmailNotificationFactory {
         Self::create_notification(recipient_email, &template, vars)
     }
     
+    /// Create a return approval email
+    #[allow(clippy::too_many_arguments)]
+    pub fn return_approved(
+        recipient_email: &str,
+        customer_name: &str,
+        rma_number: &str,
+        order_number: &str,
+        refund_amount: &str,
+        return_instructions: &str,
+        tracking_number: &str,
+        decision_notes: &str,
+    ) -> Result<Notification> {
+        let template = NotificationTemplate::load("return_approved_html")?;
+        
+        let mut vars = TemplateVariables::new();
+        vars.insert("customer_name", customer_name);
+        vars.insert("rma_number", rma_number);
+        vars.insert("order_number", order_number);
+        vars.insert("refund_amount", refund_amount);
+        vars.insert("return_instructions", return_instructions);
+        vars.insert("tracking_number", tracking_number);
+        vars.insert("decision_notes", decision_notes);
+        vars.insert("company_name", "R Commerce");
+        vars.insert("support_email", "support@rcommerce.local");
+        
+        Self::create_notification(recipient_email, &template, vars)
+    }
+    
+    /// Create a return rejection email
+    pub fn return_rejected(
+        recipient_email: &str,
+        customer_name: &str,
+        rma_number: &str,
+        order_number: &str,
+        decision_notes: &str,
+    ) -> Result<Notification> {
+        let template = NotificationTemplate::load("return_rejected_html")?;
+        
+        let mut vars = TemplateVariables::new();
+        vars.insert("customer_name", customer_name);
+        vars.insert("rma_number", rma_number);
+        vars.insert("order_number", order_number);
+        vars.insert("decision_notes", decision_notes);
+        vars.insert("company_name", "R Commerce");
+        vars.insert("support_email", "support@rcommerce.local");
+        
+        Self::create_notification(recipient_email, &template, vars)
+    }
+    
+    /// Create a refund confirmation email
+    pub fn refund_processed(
+        recipient_email: &str,
+        customer_name: &str,
+        order_number: &str,
+        refund_amount: &str,
+        refund_method: &str,
+        processing_time: &str,
+    ) -> Result<Notification> {
+        let template = NotificationTemplate::load("refund_processed_html")?;
+        
+        let mut vars = TemplateVariables::new();
+        vars.insert("customer_name", customer_name);
+        vars.insert("order_number", order_number);
+        vars.insert("refund_amount", refund_amount);
+        vars.insert("refund_method", refund_method);
+        vars.insert("processing_time", processing_time);
+        vars.insert("company_name", "R Commerce");
+        vars.insert("support_email", "support@rcommerce.local");
+        
+        Self::create_notification(recipient_email, &template, vars)
+    }
+    
     /// Create a welcome email for new customers
     pub fn welcome(
         recipient_email: &str,
@@ -238,7 +334,7 @@ impl EmailNotificationFactory {
                 item.name, item.sku
             ));
             html.push_str(&format!("<td style='text-align:center;padding:8px;'>{}</td>", item.quantity));
-            html.push_str(&format!("<td style='text-align:right;padding:8px;'>${}</td>", item.price));
+            html.push_str(&format!("<td style='text-align:right;padding:8px;'>{}</td>", item.price));
             html.push_str("</tr>");
         }
         
@@ -264,6 +360,7 @@ pub struct OrderItem {
     pub name: String,
     pub sku: String,
     pub quantity: i32,
+    /// Price formatted for display (see `FormattingService`)
     pub price: String,
 }
 
@@ -308,7 +405,7 @@ mod tests {
                 name: "Test Product".to_string(),
                 sku: "TEST-001".to_string(),
                 quantity: 2,
-                price: "29.99".to_string(),
+                price: "$29.99".to_string(),
             },
         ];
         
@@ -0,0 +1,258 @@
+//! In-app notifications
+//!
+//! Notifications for the bell in the admin UI and customer portal. They are
+//! stored per customer (staff sign in as customers too) until read. The
+//! trigger of migration 077 announces new and read notifications on the
+//! `in_app_notifications` Postgres channel; [`InAppFeed`] forwards those to
+//! the sockets open on this instance, which push the notification and the
+//! new unread count to their owner.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::notification::{Notification, NotificationCategory};
+use crate::{Error, Result};
+
+/// Postgres channel the trigger notifies
+pub const IN_APP_CHANNEL: &str = "in_app_notifications";
+
+/// Wait before listening again after the Postgres connection is lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// A stored in-app notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InAppNotification {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// The queued notification it was sent as, if any
+    pub notification_id: Option<Uuid>,
+    pub category: String,
+    pub title: String,
+    pub body: String,
+    /// Where the bell takes the reader, e.g. an admin or account page
+    pub link: Option<String>,
+    pub data: serde_json::Value,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A notification to add to someone's bell
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NewInAppNotification {
+    pub customer_id: Uuid,
+    #[serde(skip)]
+    pub notification_id: Option<Uuid>,
+    #[serde(default = "default_category")]
+    pub category: NotificationCategory,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    pub link: Option<String>,
+    #[serde(default = "empty_data")]
+    pub data: serde_json::Value,
+}
+
+fn default_category() -> NotificationCategory {
+    NotificationCategory::Account
+}
+
+fn empty_data() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+impl NewInAppNotification {
+    pub fn new(customer_id: Uuid, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            customer_id,
+            notification_id: None,
+            category: NotificationCategory::Account,
+            title: title.into(),
+            body: body.into(),
+            link: None,
+            data: empty_data(),
+        }
+    }
+
+    pub fn with_category(mut self, category: NotificationCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// An `InApp` notification, whose recipient is the customer id; the
+    /// subject becomes the title and a `link` in the metadata the link
+    pub fn from_notification(notification: &Notification) -> Result<Self> {
+        let customer_id = notification
+            .recipient
+            .parse()
+            .map_err(|_| Error::validation(format!("In-app recipient is not a customer id: {}", notification.recipient)))?;
+        let mut new = Self::new(customer_id, notification.subject.clone(), notification.body.clone())
+            .with_category(NotificationCategory::of(notification))
+            .with_data(notification.metadata.clone());
+        new.notification_id = Some(notification.id);
+        new.link = notification.metadata.get("link").and_then(|link| link.as_str()).map(str::to_string);
+        Ok(new)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.title.trim().is_empty() {
+            return Err(Error::validation("Notification title is required"));
+        }
+        if self.title.chars().count() > 255 {
+            return Err(Error::validation("Notification title must be at most 255 characters"));
+        }
+        Ok(())
+    }
+}
+
+/// A change to someone's notifications, as announced by the trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InAppChange {
+    pub customer_id: Uuid,
+    /// Set for a new notification; otherwise some were read
+    pub notification_id: Option<Uuid>,
+}
+
+/// Messages from a notification bell
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InAppClientMessage {
+    Ping,
+}
+
+/// Messages to a notification bell
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InAppServerMessage {
+    /// A new notification
+    Notification { notification: InAppNotification, unread_count: i64 },
+    /// Sent when the socket opens and after notifications are read
+    UnreadCount { unread_count: i64 },
+    Error { message: String },
+    Pong,
+}
+
+/// Fan-out of in-app notification changes to the open sockets
+#[derive(Clone)]
+pub struct InAppFeed {
+    sender: broadcast::Sender<InAppChange>,
+}
+
+impl InAppFeed {
+    /// `buffer` is how many changes a slow connection may fall behind by
+    pub fn new(buffer: usize) -> Self {
+        let (sender, _) = broadcast::channel(buffer.max(1));
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InAppChange> {
+        self.sender.subscribe()
+    }
+
+    /// Send a change to every connection; returns how many got it
+    pub fn publish(&self, change: InAppChange) -> usize {
+        self.sender.send(change).unwrap_or(0)
+    }
+
+    /// Open notification sockets
+    pub fn connection_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Forward the trigger's changes until the server stops, listening
+    /// again after a lost connection
+    pub async fn listen(&self, pool: PgPool) {
+        loop {
+            if let Err(e) = self.forward(&pool).await {
+                tracing::warn!("In-app notification listener stopped, retrying in {:?}: {}", RECONNECT_DELAY, e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn forward(&self, pool: &PgPool) -> Result<()> {
+        let mut listener = PgListener::connect_with(pool).await.map_err(Error::Database)?;
+        listener.listen(IN_APP_CHANNEL).await.map_err(Error::Database)?;
+        tracing::info!("Listening for in-app notifications on {}", IN_APP_CHANNEL);
+        loop {
+            let notification = listener.recv().await.map_err(Error::Database)?;
+            match serde_json::from_str::<InAppChange>(notification.payload()) {
+                Ok(change) => {
+                    self.publish(change);
+                }
+                Err(e) => tracing::warn!("Ignored malformed in-app notification change: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::NotificationChannel;
+
+    #[test]
+    fn test_from_notification() {
+        let customer_id = Uuid::new_v4();
+        let notification = Notification::new(
+            NotificationChannel::InApp,
+            customer_id.to_string(),
+            "Your order shipped".to_string(),
+            "Order #1001 is on its way".to_string(),
+        )
+        .with_metadata(serde_json::json!({ "type": "order_shipped", "link": "/account/orders/1001" }));
+
+        let new = NewInAppNotification::from_notification(&notification).unwrap();
+        assert_eq!(new.customer_id, customer_id);
+        assert_eq!(new.notification_id, Some(notification.id));
+        assert_eq!(new.category, NotificationCategory::ShippingUpdates);
+        assert_eq!(new.title, "Your order shipped");
+        assert_eq!(new.link.as_deref(), Some("/account/orders/1001"));
+        assert!(new.validate().is_ok());
+
+        let mut email = notification.clone();
+        email.recipient = "customer@example.com".to_string();
+        assert!(NewInAppNotification::from_notification(&email).is_err());
+        assert!(NewInAppNotification::new(customer_id, " ", "").validate().is_err());
+    }
+
+    #[test]
+    fn test_changes_and_messages() {
+        let customer_id = Uuid::new_v4();
+        let change: InAppChange =
+            serde_json::from_str(&format!(r#"{{"customer_id":"{}","notification_id":null}}"#, customer_id)).unwrap();
+        assert_eq!(change, InAppChange { customer_id, notification_id: None });
+
+        let message: InAppClientMessage = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert_eq!(message, InAppClientMessage::Ping);
+        let sent = serde_json::to_value(InAppServerMessage::UnreadCount { unread_count: 3 }).unwrap();
+        assert_eq!(sent, serde_json::json!({ "type": "unread_count", "unread_count": 3 }));
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let feed = InAppFeed::new(8);
+        let change = InAppChange { customer_id: Uuid::new_v4(), notification_id: Some(Uuid::new_v4()) };
+        assert_eq!(feed.publish(change), 0);
+
+        let mut receiver = feed.subscribe();
+        assert_eq!(feed.connection_count(), 1);
+        assert_eq!(feed.publish(change), 1);
+        assert_eq!(receiver.recv().await.unwrap(), change);
+    }
+}
@@ -3,6 +3,10 @@ pub mod templates;
 pub mod service;
 pub mod types;
 pub mod email_templates;
+pub mod catalog;
+pub mod theme;
+pub mod preferences;
+pub mod in_app;
 
 #[cfg(test)]
 mod tests;
@@ -13,6 +17,9 @@ pub use service::NotificationService;
 pub use templates::{NotificationTemplate, TemplateVariables};
 pub use types::{NotificationMessage, NotificationResult, DeliveryStatus, DeliveryAttempt, NotificationPriority, Notification, Recipient, NotificationPreferences};
 pub use email_templates::{EmailNotificationFactory, EmailTemplateType, OrderItem, Address};
+pub use theme::EmailTheme;
+pub use preferences::{NotificationCategory, UnsubscribeLinks};
+pub use in_app::{InAppFeed, InAppNotification, NewInAppNotification};
 
 /// Notification channels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
@@ -0,0 +1,367 @@
+//! Notification preferences and unsubscribe links
+//!
+//! A notification's category comes from the `type` in its metadata.
+//! Account notices (password resets, dunning, staff alerts) are always
+//! sent; order, shipping and marketing notifications follow the customer's
+//! `CustomerNotificationPreferences`, and wait out their quiet hours (UTC)
+//! unless they are silent in-app ones.
+//! Marketing emails carry a signed one-click unsubscribe link (RFC 8058).
+//! The link only unsubscribes from its own category, and expires; other
+//! preference changes need the customer to sign in.
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use ring::{hkdf, hmac};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{CustomerNotificationPreferences, UpdateNotificationPreferences};
+use crate::notification::{Notification, NotificationChannel};
+use crate::{Config, Error, Result};
+
+/// Value of the `List-Unsubscribe-Post` header for one-click unsubscribes
+pub const LIST_UNSUBSCRIBE_POST: &str = "List-Unsubscribe=One-Click";
+
+/// Metadata key holding a notification's unsubscribe link
+const UNSUBSCRIBE_KEY: &str = "list_unsubscribe";
+
+/// HKDF label of the link signing key, so it differs from the JWT key it is derived from
+const LINK_KEY_LABEL: &[u8] = b"rcommerce unsubscribe links";
+
+/// What a notification is about, and so which preference covers it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// Security, billing and staff notices; always sent
+    Account,
+    OrderUpdates,
+    ShippingUpdates,
+    Marketing,
+}
+
+impl NotificationCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Account => "account",
+            Self::OrderUpdates => "order_updates",
+            Self::ShippingUpdates => "shipping_updates",
+            Self::Marketing => "marketing",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "account" => Some(Self::Account),
+            "order_updates" => Some(Self::OrderUpdates),
+            "shipping_updates" => Some(Self::ShippingUpdates),
+            "marketing" => Some(Self::Marketing),
+            _ => None,
+        }
+    }
+
+    /// Category of a notification type (`order_shipped`, `abandoned_cart`...)
+    pub fn for_type(kind: &str) -> Self {
+        match kind.trim_end_matches("_html") {
+            "order_shipped" | "order_split" | "backorder_shipped" | "order_delivered" | "order_ready_for_pickup"
+            | "order_picked_up" => Self::ShippingUpdates,
+            "order_confirmation" | "order_cancelled" | "payment_successful" | "payment_failed" | "refund_processed"
+            | "return_approved" | "return_rejected" | "subscription_created" | "subscription_renewal"
+            | "subscription_cancelled" => Self::OrderUpdates,
+            "abandoned_cart" | "marketing" | "newsletter" => Self::Marketing,
+            _ => Self::Account,
+        }
+    }
+
+    /// Category of a notification, from the `type` in its metadata
+    pub fn of(notification: &Notification) -> Self {
+        notification
+            .metadata
+            .get("type")
+            .and_then(|kind| kind.as_str())
+            .map(Self::for_type)
+            .unwrap_or(Self::Account)
+    }
+
+    /// Whether the preferences let notifications of this category through
+    pub fn enabled_in(&self, preferences: &CustomerNotificationPreferences) -> bool {
+        match self {
+            Self::Account => true,
+            Self::OrderUpdates => preferences.order_updates,
+            Self::ShippingUpdates => preferences.shipping_updates,
+            Self::Marketing => preferences.marketing_emails,
+        }
+    }
+
+    /// The preference change that stops notifications of this category
+    pub fn opt_out(&self) -> Result<UpdateNotificationPreferences> {
+        let mut update = UpdateNotificationPreferences::default();
+        match self {
+            Self::Account => return Err(Error::validation("Account notifications can't be unsubscribed from")),
+            Self::OrderUpdates => update.order_updates = Some(false),
+            Self::ShippingUpdates => update.shipping_updates = Some(false),
+            Self::Marketing => update.marketing_emails = Some(false),
+        }
+        Ok(update)
+    }
+}
+
+/// What to do with a notification given the recipient's preferences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreferenceDecision {
+    Send,
+    /// Not wanted; the reason is recorded on the notification
+    Suppress(&'static str),
+    /// Inside quiet hours; send when they end
+    Defer(DateTime<Utc>),
+}
+
+/// Check a notification's channel and category against the preferences
+pub fn decide(
+    preferences: &CustomerNotificationPreferences,
+    channel: NotificationChannel,
+    category: NotificationCategory,
+    now: DateTime<Utc>,
+) -> PreferenceDecision {
+    if category == NotificationCategory::Account {
+        return PreferenceDecision::Send;
+    }
+    let channel_enabled = match channel {
+        NotificationChannel::Email => preferences.email_enabled,
+        NotificationChannel::Sms => preferences.sms_enabled,
+        NotificationChannel::Push => preferences.push_enabled,
+        NotificationChannel::Webhook | NotificationChannel::InApp => true,
+    };
+    if !channel_enabled {
+        return PreferenceDecision::Suppress("Recipient turned off this channel");
+    }
+    if !category.enabled_in(preferences) {
+        return PreferenceDecision::Suppress("Recipient unsubscribed from these notifications");
+    }
+    match (preferences.quiet_hours_start, preferences.quiet_hours_end) {
+        (Some(start), Some(end)) if channel != NotificationChannel::InApp && in_quiet_hours(start, end, now.time()) => {
+            let mut until = now.date_naive().and_time(end).and_utc();
+            if until <= now {
+                until += Duration::days(1);
+            }
+            PreferenceDecision::Defer(until)
+        }
+        _ => PreferenceDecision::Send,
+    }
+}
+
+/// Whether `time` falls in quiet hours, which may span midnight
+fn in_quiet_hours(start: NaiveTime, end: NaiveTime, time: NaiveTime) -> bool {
+    if start < end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// A verified unsubscribe token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsubscribeToken {
+    pub customer_id: Uuid,
+    pub category: NotificationCategory,
+}
+
+/// Signs and verifies unsubscribe links
+#[derive(Clone)]
+pub struct UnsubscribeLinks {
+    key: hmac::Key,
+    public_url: String,
+    ttl: Duration,
+}
+
+impl UnsubscribeLinks {
+    /// Links valid for `ttl`, signed with a key derived from `secret`
+    pub fn new(secret: &str, public_url: impl Into<String>, ttl: Duration) -> Self {
+        let key = hkdf::Salt::new(hkdf::HKDF_SHA256, &[])
+            .extract(secret.as_bytes())
+            .expand(&[LINK_KEY_LABEL], hmac::HMAC_SHA256)
+            .expect("HMAC-SHA256 key length is a valid HKDF output length")
+            .into();
+        Self {
+            key,
+            public_url: public_url.into(),
+            ttl,
+        }
+    }
+
+    /// Links signed with a key derived from the JWT secret under
+    /// `notifications.unsubscribe.public_url`
+    pub fn from_config(config: &Config) -> Self {
+        let unsubscribe = &config.notifications.unsubscribe;
+        Self::new(
+            &config.security.jwt.secret,
+            unsubscribe.public_url.clone(),
+            Duration::days(unsubscribe.link_ttl_days),
+        )
+    }
+
+    /// `<customer id>.<category>.<expiry (Unix time)>.<signature>`
+    pub fn token(&self, customer_id: Uuid, category: NotificationCategory, now: DateTime<Utc>) -> String {
+        let payload = format!("{}.{}.{}", customer_id, category.as_str(), (now + self.ttl).timestamp());
+        let signature = hmac::sign(&self.key, payload.as_bytes());
+        format!("{}.{}", payload, hex::encode(signature))
+    }
+
+    /// One-click unsubscribe URL for the customer and category
+    pub fn url(&self, customer_id: Uuid, category: NotificationCategory, now: DateTime<Utc>) -> String {
+        format!(
+            "{}/api/v1/unsubscribe/{}",
+            self.public_url.trim_end_matches('/'),
+            self.token(customer_id, category, now)
+        )
+    }
+
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Result<UnsubscribeToken> {
+        let invalid = || Error::unauthorized("Invalid unsubscribe link");
+        let (payload, signature) = token.rsplit_once('.').ok_or_else(invalid)?;
+        let signature = hex::decode(signature).map_err(|_| invalid())?;
+        hmac::verify(&self.key, payload.as_bytes(), &signature).map_err(|_| invalid())?;
+
+        let mut parts = payload.splitn(3, '.');
+        let (Some(customer_id), Some(category), Some(expires)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let expires: i64 = expires.parse().map_err(|_| invalid())?;
+        if now.timestamp() >= expires {
+            return Err(Error::unauthorized("Unsubscribe link has expired"));
+        }
+        Ok(UnsubscribeToken {
+            customer_id: customer_id.parse().map_err(|_| invalid())?,
+            category: NotificationCategory::parse(category).ok_or_else(invalid)?,
+        })
+    }
+}
+
+/// Add an unsubscribe link to the text and HTML bodies, and record it for
+/// the `List-Unsubscribe` headers
+pub fn add_unsubscribe_link(notification: &mut Notification, url: &str) {
+    notification.body.push_str(&format!("\n\nUnsubscribe: {}", url));
+    if let Some(ref mut html) = notification.html_body {
+        let link = format!(
+            r#"<p style="text-align: center; font-size: 12px;"><a href="{}">Unsubscribe</a></p>"#,
+            url
+        );
+        match html.rfind("</body>") {
+            Some(at) => html.insert_str(at, &link),
+            None => html.push_str(&link),
+        }
+    }
+    if !notification.metadata.is_object() {
+        notification.metadata = serde_json::json!({});
+    }
+    notification.metadata[UNSUBSCRIBE_KEY] = serde_json::json!(url);
+}
+
+/// `List-Unsubscribe` and `List-Unsubscribe-Post` headers for an email
+/// with an unsubscribe link
+pub fn unsubscribe_headers(notification: &Notification) -> Vec<(&'static str, String)> {
+    match notification.metadata.get(UNSUBSCRIBE_KEY).and_then(|url| url.as_str()) {
+        Some(url) => vec![
+            ("List-Unsubscribe", format!("<{}>", url)),
+            ("List-Unsubscribe-Post", LIST_UNSUBSCRIBE_POST.to_string()),
+        ],
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_category_from_metadata() {
+        let notification = Notification::new(NotificationChannel::Email, "a@example.com".into(), "s".into(), "b".into())
+            .with_metadata(serde_json::json!({ "type": "order_confirmation_html" }));
+        assert_eq!(NotificationCategory::of(&notification), NotificationCategory::OrderUpdates);
+        assert_eq!(NotificationCategory::for_type("order_delivered"), NotificationCategory::ShippingUpdates);
+        assert_eq!(NotificationCategory::for_type("abandoned_cart"), NotificationCategory::Marketing);
+        assert_eq!(NotificationCategory::for_type("password_reset"), NotificationCategory::Account);
+        assert!(NotificationCategory::Account.opt_out().is_err());
+    }
+
+    #[test]
+    fn test_decide() {
+        let mut preferences = CustomerNotificationPreferences::default();
+        let email = NotificationChannel::Email;
+        assert_eq!(decide(&preferences, email, NotificationCategory::Marketing, at(12, 0)), PreferenceDecision::Send);
+
+        preferences.marketing_emails = false;
+        preferences.email_enabled = false;
+        assert!(matches!(
+            decide(&preferences, email, NotificationCategory::Marketing, at(12, 0)),
+            PreferenceDecision::Suppress(_)
+        ));
+        assert_eq!(decide(&preferences, email, NotificationCategory::Account, at(12, 0)), PreferenceDecision::Send);
+
+        // Quiet hours spanning midnight defer until they end
+        preferences.email_enabled = true;
+        preferences.quiet_hours_start = NaiveTime::from_hms_opt(22, 0, 0);
+        preferences.quiet_hours_end = NaiveTime::from_hms_opt(7, 0, 0);
+        let category = NotificationCategory::ShippingUpdates;
+        assert_eq!(
+            decide(&preferences, email, category, at(23, 30)),
+            PreferenceDecision::Defer(at(7, 0) + Duration::days(1))
+        );
+        assert_eq!(decide(&preferences, email, category, at(6, 0)), PreferenceDecision::Defer(at(7, 0)));
+        assert_eq!(decide(&preferences, email, category, at(7, 0)), PreferenceDecision::Send);
+        assert_eq!(decide(&preferences, NotificationChannel::InApp, category, at(23, 30)), PreferenceDecision::Send);
+    }
+
+    #[test]
+    fn test_unsubscribe_token() {
+        let links = UnsubscribeLinks::new("secret", "https://shop.example.com/", Duration::days(30));
+        let customer_id = Uuid::new_v4();
+        let now = at(12, 0);
+        let token = links.token(customer_id, NotificationCategory::Marketing, now);
+        let verified = links.verify(&token, now).unwrap();
+        assert_eq!(verified.customer_id, customer_id);
+        assert_eq!(verified.category, NotificationCategory::Marketing);
+        assert!(links
+            .url(customer_id, NotificationCategory::Marketing, now)
+            .starts_with("https://shop.example.com/api/v1/unsubscribe/"));
+
+        let tampered = token.replace("marketing", "order_updates");
+        assert!(links.verify(&tampered, now).is_err());
+        assert!(UnsubscribeLinks::new("other", "", Duration::days(30)).verify(&token, now).is_err());
+        assert!(links.verify("not-a-token", now).is_err());
+
+        // Links stop working once they expire
+        assert!(links.verify(&token, now + Duration::days(29)).is_ok());
+        assert!(links.verify(&token, now + Duration::days(30)).is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_key_is_not_the_secret() {
+        // A MAC keyed with the raw secret (as JWTs are) is not a valid link signature
+        let links = UnsubscribeLinks::new("secret", "", Duration::days(30));
+        let now = at(12, 0);
+        let token = links.token(Uuid::new_v4(), NotificationCategory::Marketing, now);
+        let (payload, _) = token.rsplit_once('.').unwrap();
+        let raw_key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        let forged = format!("{}.{}", payload, hex::encode(hmac::sign(&raw_key, payload.as_bytes())));
+        assert!(links.verify(&forged, now).is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_link() {
+        let mut notification =
+            Notification::new(NotificationChannel::Email, "a@example.com".into(), "s".into(), "Hello".into())
+                .with_html_body("<html><body><p>Hello</p></body></html>".to_string());
+        assert!(unsubscribe_headers(&notification).is_empty());
+
+        add_unsubscribe_link(&mut notification, "https://shop.example.com/u");
+        assert!(notification.body.ends_with("Unsubscribe: https://shop.example.com/u"));
+        assert!(notification.html_body.as_deref().unwrap().contains(r#"<a href="https://shop.example.com/u">Unsubscribe</a></p></body>"#));
+        let headers = unsubscribe_headers(&notification);
+        assert_eq!(headers[0], ("List-Unsubscribe", "<https://shop.example.com/u>".to_string()));
+        assert_eq!(headers[1].1, LIST_UNSUBSCRIBE_POST);
+    }
+}
@@ -1,14 +1,19 @@
+use std::sync::Arc;
+
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use sqlx::Row;
 
 use crate::{Result, Error};
-use crate::notification::{Notification, NotificationChannel, DeliveryStatus, DeliveryAttempt, NotificationPriority, TemplateVariables, Recipient};
+use crate::notification::{Notification, NotificationChannel, DeliveryStatus, DeliveryAttempt, NotificationPriority, TemplateVariables, Recipient, NewInAppNotification};
 use crate::notification::channels::{EmailChannel, SmsChannel, WebhookChannel};
 use crate::notification::templates::{NotificationTemplate};
 use crate::models::customer::Customer;
 use crate::models::address::Address;
 use crate::order::{Order, OrderItem, Fulfillment};
+use crate::notification::preferences::{add_unsubscribe_link, decide, NotificationCategory, PreferenceDecision, UnsubscribeLinks};
+use crate::repository::{AccountRepository, InAppNotificationRepository, NotificationRepository, PostgresInAppNotificationRepository, PostgresNotificationRepository};
+use crate::services::FormattingService;
 
 /// Main notification service
 pub struct NotificationService {
@@ -19,6 +24,8 @@ pub struct NotificationService {
     #[allow(dead_code)]
     webhook_channel: WebhookChannel,
     db: sqlx::PgPool,
+    /// Recipients' preferences and the links to unsubscribe from marketing emails
+    preferences: Option<(Arc<dyn AccountRepository>, UnsubscribeLinks)>,
 }
 
 impl NotificationService {
@@ -33,9 +40,17 @@ impl NotificationService {
             sms_channel,
             webhook_channel,
             db,
+            preferences: None,
         }
     }
     
+    /// Respect customers' notification preferences and add unsubscribe
+    /// links to marketing emails
+    pub fn with_preferences(mut self, accounts: Arc<dyn AccountRepository>, links: UnsubscribeLinks) -> Self {
+        self.preferences = Some((accounts, links));
+        self
+    }
+    
     /// Send a notification
     pub async fn send(&self, notification: &Notification) -> Result<DeliveryAttempt> {
         // Create delivery attempt
@@ -44,10 +59,20 @@ impl NotificationService {
             DeliveryStatus::Pending
         );
         
+        let Some(notification) = self.apply_preferences(notification, &mut attempt).await? else {
+            return Ok(attempt);
+        };
+        let notification = &notification;
+        
         // Send based on channel
         match notification.channel {
             NotificationChannel::Email => {
-                self.email_channel.send(notification).await?;
+                if let Some(message_id) = self.email_channel.deliver(notification).await? {
+                    // Bounce and complaint webhooks may only carry the provider's id
+                    PostgresNotificationRepository::new(self.db.clone())
+                        .set_provider_message_id(notification.id, self.email_channel.provider_name(), &message_id)
+                        .await?;
+                }
                 attempt.mark_sent();
                 attempt.mark_delivered(); // Simplified - email is "delivered" when sent
             }
@@ -61,12 +86,69 @@ impl NotificationService {
                 attempt.mark_sent();
                 attempt.mark_delivered();
             }
+            NotificationChannel::InApp => {
+                // Stored for the recipient's bell; open sockets get it from the table's trigger
+                PostgresInAppNotificationRepository::new(self.db.clone())
+                    .create(&NewInAppNotification::from_notification(notification)?)
+                    .await?;
+                attempt.mark_sent();
+                attempt.mark_delivered();
+            }
             _ => return Err(Error::not_implemented("Notification channel not supported"))
         }
         
         Ok(attempt)
     }
     
+    /// Check the recipient's preferences; `None` if the notification is
+    /// suppressed or deferred past their quiet hours instead of sent now
+    async fn apply_preferences(
+        &self,
+        notification: &Notification,
+        attempt: &mut DeliveryAttempt,
+    ) -> Result<Option<Notification>> {
+        let mut notification = notification.clone();
+        let Some((accounts, links)) = &self.preferences else {
+            return Ok(Some(notification));
+        };
+        let category = NotificationCategory::of(&notification);
+        if category == NotificationCategory::Account {
+            return Ok(Some(notification));
+        }
+        // Staff and guests have no preferences to respect
+        let customer_id = match notification.channel {
+            NotificationChannel::InApp => notification.recipient.parse().ok(),
+            _ => accounts.customer_for_address(&notification.recipient).await?,
+        };
+        let Some(customer_id) = customer_id else {
+            return Ok(Some(notification));
+        };
+        let preferences = accounts.notification_preferences(customer_id).await?.unwrap_or_default();
+        
+        match decide(&preferences, notification.channel, category, Utc::now()) {
+            PreferenceDecision::Send => {}
+            PreferenceDecision::Suppress(reason) => {
+                log::info!("Notification {} not sent: {}", notification.id, reason);
+                notification.status = DeliveryStatus::Suppressed;
+                notification.error_message = Some(reason.to_string());
+                PostgresNotificationRepository::new(self.db.clone()).update(&notification).await?;
+                attempt.status = DeliveryStatus::Suppressed;
+                attempt.error = Some(reason.to_string());
+                return Ok(None);
+            }
+            PreferenceDecision::Defer(until) => {
+                log::info!("Notification {} deferred to {} for the recipient's quiet hours", notification.id, until);
+                PostgresNotificationRepository::new(self.db.clone()).update(&notification.schedule(until)).await?;
+                return Ok(None);
+            }
+        }
+        
+        if category == NotificationCategory::Marketing && notification.channel == NotificationChannel::Email {
+            add_unsubscribe_link(&mut notification, &links.url(customer_id, category, Utc::now()));
+        }
+        Ok(Some(notification))
+    }
+    
     /// Send notification with retry logic
     pub async fn send_with_retry(&self, notification: &Notification, max_retries: u32) -> Result<DeliveryAttempt> {
         let mut attempt = self.send(notification).await?;
@@ -104,6 +186,7 @@ impl NotificationService {
                 NotificationChannel::Email => recipient.email.clone().unwrap_or_default(),
                 NotificationChannel::Sms => recipient.phone.clone().unwrap_or_default(),
                 NotificationChannel::Webhook => recipient.webhook_url.clone().unwrap_or_default(),
+                NotificationChannel::InApp => recipient.id.to_string(),
                 _ => String::new(),
             };
             
@@ -269,7 +352,7 @@ impl NotificationFactory {
     }
 
     /// Order confirmation notification (plain text)
-    pub fn order_confirmation(order: &Order, recipient: Recipient) -> Notification {
+    pub fn order_confirmation(order: &Order, recipient: Recipient, formatting: &FormattingService) -> Notification {
         let channel = recipient.primary_channel();
         let recipient_addr = Self::get_recipient_address(&recipient, channel);
         
@@ -278,9 +361,9 @@ impl NotificationFactory {
             recipient_addr,
             format!("Order Confirmed: {}", order.order_number),
             format!(
-                "Your order {} has been confirmed. Total: ${}",
+                "Your order {} has been confirmed. Total: {}",
                 order.order_number,
-                order.total
+                formatting.format_price(order.total, &order.currency, None)
             ),
         )
         .with_priority(NotificationPriority::High)
@@ -298,6 +381,7 @@ impl NotificationFactory {
         shipping_address: &Address,
         billing_address: &Address,
         order_items: &[OrderItem],
+        formatting: &FormattingService,
     ) -> Result<Notification> {
         // Load the HTML template
         let template = NotificationTemplate::load("order_confirmation_html")
@@ -305,11 +389,11 @@ impl NotificationFactory {
         
         // Prepare template variables
         let mut variables = TemplateVariables::new();
-        variables.add_order(order);
+        variables.add_order(order, formatting);
         variables.add_customer(customer);
         variables.add_addresses(shipping_address, billing_address);
-        variables.add_order_items(order_items);
-        variables.add_totals(order);
+        variables.add_order_items(order_items, &order.currency, formatting);
+        variables.add_totals(order, formatting);
         variables.add_company_info("PDG Global Limited", "support@rcommerce.app");
         
         // Render templates
@@ -367,6 +451,191 @@ impl NotificationFactory {
         }))
     }
     
+    /// Order shipping in more than one parcel, or partly backordered
+    pub fn order_split(
+        order_id: Uuid,
+        order_number: &str,
+        parcels: usize,
+        shipping: &[String],
+        backordered: &[String],
+        recipient: Recipient,
+    ) -> Notification {
+        let mut body = if parcels > 1 {
+            format!("Your order {} ships from more than one warehouse, in {} parcels.", order_number, parcels)
+        } else {
+            format!("Part of your order {} is on its way.", order_number)
+        };
+        if !shipping.is_empty() {
+            body.push_str(&format!("\n\nShipping now:\n- {}", shipping.join("\n- ")));
+        }
+        if !backordered.is_empty() {
+            body.push_str(&format!(
+                "\n\nBackordered, shipping as soon as it is back in stock:\n- {}",
+                backordered.join("\n- ")
+            ));
+        }
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Your order {} ships in parts", order_number),
+            body,
+        )
+        .with_metadata(serde_json::json!({
+            "order_id": order_id,
+            "type": "order_split",
+        }))
+    }
+    
+    /// Backordered items of an order shipped
+    pub fn backorder_shipped(
+        order_id: Uuid,
+        order_number: &str,
+        shipping: &[String],
+        still_backordered: &[String],
+        recipient: Recipient,
+    ) -> Notification {
+        let mut body = format!(
+            "Items of your order {} are back in stock and on their way:\n- {}",
+            order_number,
+            shipping.join("\n- ")
+        );
+        if !still_backordered.is_empty() {
+            body.push_str(&format!(
+                "\n\nStill backordered:\n- {}",
+                still_backordered.join("\n- ")
+            ));
+        }
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Backordered items of order {} shipped", order_number),
+            body,
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "order_id": order_id,
+            "type": "backorder_shipped",
+        }))
+    }
+    
+    /// A parcel was handed to the carrier, per its tracking
+    pub fn parcel_shipped(
+        shipment: &crate::shipping::tracking::TrackedShipment,
+        estimated_delivery: Option<DateTime<Utc>>,
+        recipient: Recipient,
+    ) -> Notification {
+        let mut body = format!("A parcel of your order {} is on its way.", shipment.order_number);
+        if let Some(tracking) = &shipment.tracking_number {
+            body.push_str(&format!("\n\nTracking: {}", tracking));
+        }
+        if let Some(url) = &shipment.tracking_url {
+            body.push_str(&format!("\n{}", url));
+        }
+        if let Some(date) = estimated_delivery {
+            body.push_str(&format!("\n\nExpected delivery: {}", date.format("%Y-%m-%d")));
+        }
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Order Shipped: {}", shipment.order_number),
+            body,
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "order_id": shipment.order_id,
+            "fulfillment_id": shipment.id,
+            "type": "order_shipped",
+        }))
+    }
+    
+    /// A parcel was delivered, per its tracking
+    pub fn parcel_delivered(shipment: &crate::shipping::tracking::TrackedShipment, recipient: Recipient) -> Notification {
+        let mut body = format!("A parcel of your order {} has been delivered.", shipment.order_number);
+        if let Some(tracking) = &shipment.tracking_number {
+            body.push_str(&format!("\n\nTracking: {}", tracking));
+        }
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Order Delivered: {}", shipment.order_number),
+            body,
+        )
+        .with_metadata(serde_json::json!({
+            "order_id": shipment.order_id,
+            "fulfillment_id": shipment.id,
+            "type": "order_delivered",
+        }))
+    }
+    
+    /// A pickup order is waiting at its pickup location
+    pub fn ready_for_pickup(shipment: &crate::shipping::PickupShipment, recipient: Recipient) -> Notification {
+        let location = shipment.location_name.as_deref().unwrap_or("the store");
+        let mut body = format!("Your order {} is ready for pickup at {}.", shipment.order_number, location);
+        if let Some(hours) = &shipment.pickup_hours {
+            body.push_str(&format!("\n\nPickup hours: {}", hours));
+        }
+        if let Some(instructions) = &shipment.pickup_instructions {
+            body.push_str(&format!("\n\n{}", instructions));
+        }
+        body.push_str("\n\nPlease bring your order number.");
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Ready for Pickup: {}", shipment.order_number),
+            body,
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "order_id": shipment.order_id,
+            "fulfillment_id": shipment.id,
+            "type": "order_ready_for_pickup",
+        }))
+    }
+    
+    /// A pickup order was collected
+    pub fn picked_up(shipment: &crate::shipping::PickupShipment, recipient: Recipient) -> Notification {
+        let location = shipment.location_name.as_deref().unwrap_or("the store");
+        let body = format!(
+            "Your order {} was picked up at {}. Thank you for shopping with us.",
+            shipment.order_number, location
+        );
+        
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Order Picked Up: {}", shipment.order_number),
+            body,
+        )
+        .with_metadata(serde_json::json!({
+            "order_id": shipment.order_id,
+            "fulfillment_id": shipment.id,
+            "type": "order_picked_up",
+        }))
+    }
+    
     /// Low stock email
     pub fn low_stock_alert(alert: &crate::inventory::LowStockAlert, recipient: Recipient) -> Notification {
         let priority = if alert.is_critical() {
@@ -391,6 +660,248 @@ impl NotificationFactory {
             "alert_level": format!("{:?}", alert.alert_level),
         }))
     }
+    
+    /// Dropship order for a supplier without an order webhook
+    pub fn dropship_order(
+        order: &crate::inventory::DropshipOrderDetail,
+        supplier_name: &str,
+        recipient: Recipient,
+    ) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+
+        let lines: Vec<String> = order
+            .items
+            .iter()
+            .map(|item| {
+                format!(
+                    "- {} × {} ({})",
+                    item.quantity,
+                    item.title,
+                    item.supplier_sku.as_deref().unwrap_or("no SKU")
+                )
+            })
+            .collect();
+        let address = order
+            .shipping_address
+            .as_ref()
+            .and_then(|address| address.as_object())
+            .map(|address| {
+                // Addresses are stored in a few shapes; take their lines in order
+                [
+                    "first_name", "last_name", "name", "company", "address1", "address2", "line1", "line2",
+                    "street", "city", "province", "state", "zip", "postal_code", "country", "phone",
+                ]
+                .iter()
+                .filter_map(|key| address.get(*key).and_then(|value| value.as_str()))
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+            })
+            .unwrap_or_default();
+
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Dropship order {}", order.order_number),
+            format!(
+                "Hello {},\n\nPlease ship order {} to:\n\n{}\n\n{}\n\nReference: {}",
+                supplier_name,
+                order.order_number,
+                address,
+                lines.join("\n"),
+                order.dropship_order.id,
+            ),
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "dropship_order_id": order.dropship_order.id,
+            "order_id": order.dropship_order.order_id,
+            "type": "dropship_order",
+        }))
+    }
+
+    /// Purchase order emailed to a supplier, with a link to acknowledge it
+    pub fn purchase_order(
+        detail: &crate::inventory::PurchaseOrderDetail,
+        supplier_name: &str,
+        ack_url: &str,
+        recipient: Recipient,
+    ) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        let order = &detail.purchase_order;
+
+        let lines: Vec<String> = detail
+            .items
+            .iter()
+            .map(|item| {
+                format!(
+                    "- {} × {} at {}",
+                    item.quantity_ordered,
+                    item.supplier_sku.clone().unwrap_or_else(|| item.product_id.to_string()),
+                    item.unit_cost
+                        .map(|cost| format!("{} {}", cost, order.currency))
+                        .unwrap_or_else(|| "your price".to_string())
+                )
+            })
+            .collect();
+        let expected = order
+            .expected_at
+            .map(|at| format!("\nRequested delivery: {}\n", at.date_naive()))
+            .unwrap_or_default();
+
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Purchase order {}", order.po_number),
+            format!(
+                "Hello {},\n\nPlease supply purchase order {}:\n\n{}\n\nTotal: {} {}\n{}\nConfirm the quantities, \
+                 prices and delivery date at:\n{}",
+                supplier_name,
+                order.po_number,
+                lines.join("\n"),
+                detail.total,
+                order.currency,
+                expected,
+                ack_url,
+            ),
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "purchase_order_id": order.id,
+            "po_number": order.po_number,
+            "type": "purchase_order",
+        }))
+    }
+
+    /// Purchase order needing the purchasing team's attention
+    pub fn purchase_order_alert(
+        purchase_order: &crate::inventory::PurchaseOrder,
+        what: &str,
+        message: &str,
+        recipient: Recipient,
+    ) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Purchase order {}: {}", purchase_order.po_number, what),
+            message.to_string(),
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "purchase_order_id": purchase_order.id,
+            "type": "purchase_order_alert",
+        }))
+    }
+
+    /// Stock adjustment awaiting approval
+    pub fn stock_adjustment_pending(adjustment: &crate::inventory::StockAdjustmentRequest, recipient: Recipient) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("Stock adjustment awaiting approval: {:+} units", adjustment.quantity_change),
+            format!(
+                "A stock adjustment of {:+} units (currently {} available) for product {} at location {} needs your approval.\n\nReason: {}\n\nReview it at /api/v1/inventory/adjustments/{}",
+                adjustment.quantity_change,
+                adjustment.quantity_at_request,
+                adjustment.product_id,
+                adjustment.location_id,
+                adjustment.reason,
+                adjustment.id,
+            ),
+        )
+        .with_priority(NotificationPriority::High)
+        .with_metadata(serde_json::json!({
+            "adjustment_id": adjustment.id,
+            "product_id": adjustment.product_id,
+            "type": "stock_adjustment_pending",
+        }))
+    }
+    
+    /// Scheduled report with its download link
+    pub fn report_ready(
+        delivery: &crate::models::ReportDelivery,
+        subscription_name: &str,
+        download_url: &str,
+        recipient: Recipient,
+    ) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        let period = match (delivery.period_start, delivery.period_end) {
+            (Some(start), Some(end)) => format!(
+                " for {} to {}",
+                start.format("%Y-%m-%d"),
+                (end - chrono::Duration::seconds(1)).format("%Y-%m-%d")
+            ),
+            _ => format!(" as of {}", delivery.created_at.format("%Y-%m-%d %H:%M UTC")),
+        };
+        
+        Notification::new(
+            channel,
+            recipient_addr,
+            format!("{}: {}{}", subscription_name, delivery.report.title(), period),
+            format!(
+                "Your {} report{} is ready ({} rows, {}).\n\nDownload it at {}\n\nThe link expires on {}.",
+                delivery.report.title().to_lowercase(),
+                period,
+                delivery.row_count,
+                delivery.format.extension().to_uppercase(),
+                download_url,
+                delivery.expires_at.format("%Y-%m-%d"),
+            ),
+        )
+        .with_metadata(serde_json::json!({
+            "delivery_id": delivery.id,
+            "subscription_id": delivery.subscription_id,
+            "report": delivery.report,
+            "type": "report_ready",
+        }))
+    }
+    
+    /// Business metric alert firing or resolved
+    pub fn metric_alert(rule_name: &str, subject: &str, text: &str, resolved: bool, recipient: Recipient) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        let priority = if resolved {
+            NotificationPriority::Normal
+        } else {
+            NotificationPriority::Urgent
+        };
+        
+        Notification::new(channel, recipient_addr, subject.to_string(), text.to_string())
+            .with_priority(priority)
+            .with_metadata(serde_json::json!({
+                "alert": rule_name,
+                "resolved": resolved,
+                "type": "metric_alert",
+            }))
+    }
+
+    /// Incident opened or resolved by the anomaly detector, for staff
+    pub fn incident(incident_id: Uuid, subject: &str, text: &str, resolved: bool, recipient: Recipient) -> Notification {
+        let channel = recipient.primary_channel();
+        let recipient_addr = Self::get_recipient_address(&recipient, channel);
+        let priority = if resolved {
+            NotificationPriority::Normal
+        } else {
+            NotificationPriority::Urgent
+        };
+        
+        Notification::new(channel, recipient_addr, subject.to_string(), text.to_string())
+            .with_priority(priority)
+            .with_metadata(serde_json::json!({
+                "incident_id": incident_id,
+                "resolved": resolved,
+                "type": "incident",
+            }))
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 use crate::{Result, Error};
+use crate::notification::theme;
 
 /// Represents a notification template with placeholders for dynamic content substitution.
 /// 
@@ -36,8 +37,13 @@ pub struct NotificationTemplate {
 }
 
 impl NotificationTemplate {
-    /// Load from file or database
+    /// Load a template, with the installed email theme's overrides applied
     pub fn load(id: &str) -> Result<Self> {
+        Ok(theme::current().apply(Self::builtin(id)?))
+    }
+
+    /// The template compiled into the binary
+    pub fn builtin(id: &str) -> Result<Self> {
         match id {
             // Plain text templates
             "order_confirmation" => Ok(Self::order_confirmation()),
@@ -50,6 +56,8 @@ impl NotificationTemplate {
             "payment_successful_html" => Ok(Self::payment_successful_html()),
             "payment_failed_html" => Ok(Self::payment_failed_html()),
             "refund_processed_html" => Ok(Self::refund_processed_html()),
+            "return_approved_html" => Ok(Self::return_approved_html()),
+            "return_rejected_html" => Ok(Self::return_rejected_html()),
             "subscription_created_html" => Ok(Self::subscription_created_html()),
             "subscription_renewal_html" => Ok(Self::subscription_renewal_html()),
             "subscription_cancelled_html" => Ok(Self::subscription_cancelled_html()),
@@ -65,42 +73,20 @@ impl NotificationTemplate {
     
     /// Render template with variables
     pub fn render(&self, variables: &TemplateVariables) -> Result<String> {
-        let mut rendered = self.body.clone();
-        
-        for (key, value) in variables.iter() {
-            let placeholder = format!("{{{{ {} }}}}", key);
-            rendered = rendered.replace(&placeholder, value);
-        }
-        
-        Ok(rendered)
+        theme::current().render(&self.body, variables)
     }
     
     /// Render HTML template with variables
     pub fn render_html(&self, variables: &TemplateVariables) -> Result<Option<String>> {
-        if let Some(ref html_template) = self.html_body {
-            let mut rendered = html_template.clone();
-            
-            for (key, value) in variables.iter() {
-                let placeholder = format!("{{{{ {} }}}}", key);
-                rendered = rendered.replace(&placeholder, value);
-            }
-            
-            Ok(Some(rendered))
-        } else {
-            Ok(None)
+        match self.html_body {
+            Some(ref html_template) => theme::current().render(html_template, variables).map(Some),
+            None => Ok(None),
         }
     }
     
     /// Render subject line with variables
     pub fn render_subject(&self, variables: &TemplateVariables) -> Result<String> {
-        let mut rendered = self.subject.clone();
-        
-        for (key, value) in variables.iter() {
-            let placeholder = format!("{{{{ {} }}}}", key);
-            rendered = rendered.replace(&placeholder, value);
-        }
-        
-        Ok(rendered)
+        theme::current().render(&self.subject, variables)
     }
     
     /// Load HTML template from embedded file
@@ -108,8 +94,12 @@ impl NotificationTemplate {
         match path {
             "invoice.html" => Ok(include_str!("templates/invoice.html").to_string()),
             "order_shipped.html" => Ok(include_str!("templates/order_shipped.html").to_string()),
+            "order_cancelled.html" => Ok(include_str!("templates/order_cancelled.html").to_string()),
             "payment_successful.html" => Ok(include_str!("templates/payment_successful.html").to_string()),
             "payment_failed.html" => Ok(include_str!("templates/payment_failed.html").to_string()),
+            "refund_processed.html" => Ok(include_str!("templates/refund_processed.html").to_string()),
+            "return_approved.html" => Ok(include_str!("templates/return_approved.html").to_string()),
+            "return_rejected.html" => Ok(include_str!("templates/return_rejected.html").to_string()),
             "subscription_created.html" => Ok(include_str!("templates/subscription_created.html").to_string()),
             "subscription_renewal.html" => Ok(include_str!("templates/subscription_renewal.html").to_string()),
             "subscription_cancelled.html" => Ok(include_str!("templates/subscription_cancelled.html").to_string()),
@@ -136,7 +126,7 @@ Thank you for your order! Your order #{{ order_number }} has been confirmed.
 
 Order Details:
 ----------------
-Total: ${{ order_total }}
+Total: {{ order_total }}
 Items: {{ item_count }}
 
 We'll send you another email when your order ships.
@@ -168,7 +158,7 @@ Thank you for your order! Your order #{{ order_number }} has been confirmed.
 
 Order Details:
 ----------------
-Total: ${{ order_total }}
+Total: {{ order_total }}
 Items: {{ item_count }}
 
 We'll send you another email when your order ships.
@@ -217,7 +207,7 @@ The R Commerce Team
         <div class="content">
             <h1>Order Confirmed: {{ order_number }}</h1>
             <p>Thank you for your order, {{ customer_name }}!</p>
-            <p><strong>Total:</strong> ${{ order_total }}</p>
+            <p><strong>Total:</strong> {{ order_total }}</p>
         </div>
         <div class="footer">
             <p>Questions? Contact us at <a href="mailto:{{ support_email }}">{{ support_email }}</a></p>
@@ -393,6 +383,47 @@ Recommended Reorder Quantity: {{ reorder_quantity }}
         }
     }
     
+    fn return_approved_html() -> Self {
+        Self {
+            id: "return_approved_html".to_string(),
+            name: "Return Approved HTML".to_string(),
+            subject: "Return Approved: {{ rma_number }}".to_string(),
+            body: "Your return {{ rma_number }} has been approved. {{ return_instructions }}".to_string(),
+            html_body: Some(Self::load_html_template("return_approved.html").unwrap_or_else(|_| Self::get_default_html_template())),
+            channel: crate::notification::NotificationChannel::Email,
+            variables: vec![
+                "rma_number".to_string(),
+                "order_number".to_string(),
+                "customer_name".to_string(),
+                "refund_amount".to_string(),
+                "return_instructions".to_string(),
+                "tracking_number".to_string(),
+                "decision_notes".to_string(),
+                "company_name".to_string(),
+                "support_email".to_string(),
+            ],
+        }
+    }
+    
+    fn return_rejected_html() -> Self {
+        Self {
+            id: "return_rejected_html".to_string(),
+            name: "Return Rejected HTML".to_string(),
+            subject: "Return Not Approved: {{ rma_number }}".to_string(),
+            body: "Your return {{ rma_number }} was not approved. {{ decision_notes }}".to_string(),
+            html_body: Some(Self::load_html_template("return_rejected.html").unwrap_or_else(|_| Self::get_default_html_template())),
+            channel: crate::notification::NotificationChannel::Email,
+            variables: vec![
+                "rma_number".to_string(),
+                "order_number".to_string(),
+                "customer_name".to_string(),
+                "decision_notes".to_string(),
+                "company_name".to_string(),
+                "support_email".to_string(),
+            ],
+        }
+    }
+    
     fn subscription_created_html() -> Self {
         Self {
             id: "subscription_created_html".to_string(),
@@ -590,10 +621,11 @@ impl TemplateVariables {
         self.insert(key, value);
     }
     
-    pub fn add_order(&mut self, order: &crate::order::Order) {
+    /// Add order details; prices are formatted for display in the order's currency
+    pub fn add_order(&mut self, order: &crate::order::Order, formatting: &crate::services::FormattingService) {
         self.add("order_id".to_string(), order.id.to_string());
         self.add("order_number".to_string(), order.order_number.clone());
-        self.add("order_total".to_string(), order.total.to_string());
+        self.add("order_total".to_string(), formatting.format_price(order.total, &order.currency, None));
         self.add("order_currency".to_string(), order.currency.clone());
         // Add formatted date
 
@@ -619,14 +651,15 @@ impl TemplateVariables {
         self.add("current_stock".to_string(), alert.current_stock.to_string());
         self.add("threshold".to_string(), alert.threshold.to_string());
         self.add("reorder_quantity".to_string(), alert.recommended_reorder_quantity.to_string());
-        self.add("is_critical".to_string(), alert.is_critical().to_string());
+        // Empty for false: any non-empty string passes `{{#if}}`
+        self.add("is_critical".to_string(), if alert.is_critical() { "true" } else { "" });
     }
     
-    pub fn add_order_items(&mut self, items: &[crate::order::OrderItem]) {
+    pub fn add_order_items(&mut self, items: &[crate::order::OrderItem], currency: &str, formatting: &crate::services::FormattingService) {
         // Create a simple items list for plain text emails
         let items_text = items
             .iter()
-            .map(|item| format!("{} x {} - ${}", item.quantity, item.name, item.price))
+            .map(|item| format!("{} x {} - {}", item.quantity, item.name, formatting.format_price(item.price, currency, None)))
             .collect::<Vec<_>>()
             .join("\n");
         self.add("items".to_string(), items_text);
@@ -636,7 +669,7 @@ impl TemplateVariables {
             .iter()
             .map(|item| item.price * rust_decimal::Decimal::from(item.quantity))
             .sum();
-        self.add("subtotal".to_string(), format!("{:.2}", subtotal));
+        self.add("subtotal".to_string(), formatting.format_price(subtotal, currency, None));
     }
     
     pub fn add_addresses(&mut self, shipping: &crate::common::Address, billing: &crate::common::Address) {
@@ -695,11 +728,12 @@ impl TemplateVariables {
         self.add("tax_percent".to_string(), "0".to_string()); // For displaying "Tax (0%)"
     }
     
-    pub fn add_totals(&mut self, order: &crate::order::Order) {
-        self.add("subtotal".to_string(), order.subtotal.to_string());
-        self.add("tax".to_string(), order.tax_total.to_string());
-        self.add("shipping_cost".to_string(), order.shipping_total.to_string());
-        self.add("order_total".to_string(), order.total.to_string());
+    pub fn add_totals(&mut self, order: &crate::order::Order, formatting: &crate::services::FormattingService) {
+        let format = |amount| formatting.format_price(amount, &order.currency, None);
+        self.add("subtotal".to_string(), format(order.subtotal));
+        self.add("tax".to_string(), format(order.tax_total));
+        self.add("shipping_cost".to_string(), format(order.shipping_total));
+        self.add("order_total".to_string(), format(order.total));
     }
 }
 
@@ -713,7 +747,7 @@ mod tests {
         let mut vars = TemplateVariables::new();
         vars.add("customer_name".to_string(), "John Doe".to_string());
         vars.add("order_number".to_string(), "ORD-12345".to_string());
-        vars.add("order_total".to_string(), "99.99".to_string());
+        vars.add("order_total".to_string(), "$99.99".to_string());
         vars.add("item_count".to_string(), "3".to_string());
         
         let rendered = template.render(&vars).unwrap();
@@ -5,6 +5,7 @@ mod html_template_integration {
     use crate::order::{Order, OrderItem};
     use crate::models::customer::Customer;
     use crate::models::address::Address;
+    use crate::services::FormattingService;
     use rust_decimal::Decimal;
     use uuid::Uuid;
     use chrono::Utc;
@@ -85,10 +86,10 @@ mod html_template_integration {
         let billing = create_test_address();
         
         let mut variables = TemplateVariables::new();
-        variables.add_order(&order);
+        variables.add_order(&order, &FormattingService::default());
         variables.add_customer(&customer);
         variables.add_addresses(&shipping, &billing);
-        variables.add_totals(&order);
+        variables.add_totals(&order, &FormattingService::default());
         variables.add_company_info("PDG Global Limited", "support@rcommerce.app");
         
         // Check that all required variables are present
@@ -118,7 +119,7 @@ mod html_template_integration {
         // Check specific values
         assert_eq!(variables.inner.get("order_number").unwrap(), "ORD-092-331");
         assert_eq!(variables.inner.get("customer_name").unwrap(), "Alex Developer");
-        assert_eq!(variables.inner.get("order_total").unwrap(), "4120.00");
+        assert_eq!(variables.inner.get("order_total").unwrap(), "$4,120.00");
     }
     
     #[tokio::test]
@@ -143,6 +144,7 @@ mod html_template_integration {
             &shipping,
             &billing,
             &order_items,
+            &FormattingService::default(),
         );
         
         assert!(notification.is_ok());
@@ -34,7 +34,7 @@ mod notification_integration_tests {
         // Add test data
         variables.insert("order_number", "ORD-12345");
         variables.insert("customer_name", "John Doe");
-        variables.insert("order_total", "99.99");
+        variables.insert("order_total", "$99.99");
         variables.insert("order_date", "Jan 25, 2026");
         variables.insert("company_name", "R Commerce");
         variables.insert("support_email", "support@rcommerce.app");
@@ -93,6 +93,7 @@ mod notification_integration_tests {
             is_verified: true,
             last_login_at: None,
             role: crate::models::CustomerRole::Customer,
+            customer_group_id: None,
         };
         
         // Create mock addresses
@@ -0,0 +1,489 @@
+//! Email theming and template overrides
+//!
+//! The built-in email templates can be replaced per template type, with
+//! files in `notifications.email.templates_dir` or with templates stored
+//! through the admin API, which take precedence over a type's files:
+//!
+//! - `<type>.subject.txt`, `<type>.txt`, `<type>.html` - subject, text and
+//!   HTML body of a template type (see `catalog::TEMPLATE_TYPES`); parts an
+//!   override leaves out come from the built-in template
+//! - `layout.html` (or the stored `layout` template) - wraps overridden HTML
+//!   bodies, placed where it says `{{ content }}`
+//!
+//! Templates are Handlebars, and values are inserted as is (order items and
+//! addresses are HTML). Every template also sees the store branding
+//! (`company_name`, `support_email`, `logo_url`, `brand_color` and
+//! `footer_text`), which the built-in templates use for their logo, accent
+//! color and footer. Overrides that don't compile are left out, so the
+//! built-in template is sent instead; `rcommerce email validate` reports
+//! them along with unknown and missing required variables.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use handlebars::{Handlebars, Template};
+use serde::Serialize;
+
+use crate::config::{EmailBranding, EmailConfig};
+use crate::notification::catalog::{self, TemplateType, TEMPLATE_TYPES};
+use crate::notification::{NotificationChannel, NotificationTemplate, TemplateVariables};
+use crate::repository::{NotificationTemplateRecord, NotificationTemplateRepository};
+use crate::{Error, Result};
+
+/// Name of the layout, as a stored template and as a file (`layout.html`)
+pub const LAYOUT: &str = "layout";
+
+/// Where the layout places the email content
+const CONTENT_TAGS: [&str; 2] = ["{{ content }}", "{{content}}"];
+
+/// Theme `NotificationTemplate::load` applies; built-in templates only until
+/// one is installed
+static INSTALLED: RwLock<Option<Arc<EmailTheme>>> = RwLock::new(None);
+
+/// Where an override comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideSource {
+    Directory,
+    Database,
+}
+
+impl OverrideSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverrideSource::Directory => "directory",
+            OverrideSource::Database => "database",
+        }
+    }
+}
+
+/// Replacement parts of a template type
+#[derive(Debug, Clone)]
+pub struct TemplateOverride {
+    pub source: OverrideSource,
+    pub subject: Option<String>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+/// A problem with an override or the layout
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateIssue {
+    /// Template type, `layout`, or the name of a file that is neither
+    pub template: String,
+    pub source: OverrideSource,
+    pub message: String,
+}
+
+/// Branding, layout and overrides applied to the built-in email templates
+#[derive(Debug, Clone, Default)]
+pub struct EmailTheme {
+    branding: EmailBranding,
+    layout: Option<(String, OverrideSource)>,
+    overrides: HashMap<&'static str, TemplateOverride>,
+    /// Problems found while loading
+    issues: Vec<TemplateIssue>,
+}
+
+impl EmailTheme {
+    pub fn new(branding: EmailBranding) -> Self {
+        Self { branding, ..Default::default() }
+    }
+
+    /// Read the layout and overrides from a directory
+    pub fn with_directory(mut self, dir: &Path) -> Result<Self> {
+        let read_error = |e: std::io::Error| {
+            Error::Other(format!("Failed to read email templates directory {}: {}", dir.display(), e))
+        };
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+            if entry.path().is_file() {
+                files.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        files.sort();
+
+        let read = |file: &str| -> Result<Option<String>> {
+            if !files.iter().any(|f| f == file) {
+                return Ok(None);
+            }
+            std::fs::read_to_string(dir.join(file))
+                .map(Some)
+                .map_err(|e| Error::Other(format!("Failed to read email template {}: {}", file, e)))
+        };
+
+        if let Some(layout) = read("layout.html")? {
+            self.set_layout(layout, OverrideSource::Directory);
+        }
+        for template_type in TEMPLATE_TYPES {
+            let subject = read(&format!("{}.subject.txt", template_type.id))?;
+            let text = read(&format!("{}.txt", template_type.id))?;
+            let html = read(&format!("{}.html", template_type.id))?;
+            if subject.is_some() || text.is_some() || html.is_some() {
+                self.set_override(
+                    template_type,
+                    TemplateOverride {
+                        source: OverrideSource::Directory,
+                        subject: subject.map(|subject| subject.trim().to_string()),
+                        text,
+                        html,
+                    },
+                );
+            }
+        }
+
+        // A misspelled file name would otherwise be ignored without a word
+        for file in files.iter().filter(|f| f.ends_with(".html") || f.ends_with(".txt")) {
+            if file != "layout.html" && file_template_type(file).is_none() {
+                self.issues.push(TemplateIssue {
+                    template: file.clone(),
+                    source: OverrideSource::Directory,
+                    message: "Not the layout or a template type's .subject.txt, .txt or .html file".to_string(),
+                });
+            }
+        }
+        Ok(self)
+    }
+
+    /// Apply the active stored email templates; each replaces the files of
+    /// its type
+    pub fn with_records(mut self, records: &[NotificationTemplateRecord]) -> Self {
+        for record in records
+            .iter()
+            .filter(|record| record.is_active && record.channel == NotificationChannel::Email)
+        {
+            if record.name == LAYOUT {
+                if let Some(ref layout) = record.html_template {
+                    self.set_layout(layout.clone(), OverrideSource::Database);
+                }
+                continue;
+            }
+            let Some(template_type) = catalog::template_type(&record.name) else {
+                self.issues.push(TemplateIssue {
+                    template: record.name.clone(),
+                    source: OverrideSource::Database,
+                    message: "Not a template type".to_string(),
+                });
+                continue;
+            };
+            self.set_override(
+                template_type,
+                TemplateOverride {
+                    source: OverrideSource::Database,
+                    subject: non_empty(&record.subject_template),
+                    text: non_empty(&record.body_template),
+                    html: record.html_template.as_deref().and_then(non_empty),
+                },
+            );
+        }
+        self
+    }
+
+    fn set_layout(&mut self, layout: String, source: OverrideSource) {
+        match check_layout(&layout).and_then(|_| compile(&layout)) {
+            Ok(()) => self.layout = Some((layout, source)),
+            Err(e) => self.issues.push(TemplateIssue {
+                template: LAYOUT.to_string(),
+                source,
+                message: message(e),
+            }),
+        }
+    }
+
+    /// Use an override unless one of its parts doesn't compile
+    fn set_override(&mut self, template_type: &'static TemplateType, template_override: TemplateOverride) {
+        let parts = [&template_override.subject, &template_override.text, &template_override.html];
+        if let Some(e) = parts.into_iter().flatten().find_map(|part| compile(part).err()) {
+            self.issues.push(TemplateIssue {
+                template: template_type.id.to_string(),
+                source: template_override.source,
+                message: format!("{} (the built-in template is used)", message(e)),
+            });
+            return;
+        }
+        self.overrides.insert(template_type.id, template_override);
+    }
+
+    pub fn branding(&self) -> &EmailBranding {
+        &self.branding
+    }
+
+    /// The overrides, by template type
+    pub fn overrides(&self) -> &HashMap<&'static str, TemplateOverride> {
+        &self.overrides
+    }
+
+    /// A built-in template with its type's override applied
+    pub fn apply(&self, mut template: NotificationTemplate) -> NotificationTemplate {
+        let Some(template_override) = TEMPLATE_TYPES
+            .iter()
+            .find(|t| t.template_id == template.id)
+            .and_then(|t| self.overrides.get(t.id))
+        else {
+            return template;
+        };
+
+        if let Some(ref subject) = template_override.subject {
+            template.subject = subject.clone();
+        }
+        if let Some(ref text) = template_override.text {
+            template.body = text.clone();
+        }
+        if let Some(ref html) = template_override.html {
+            let html = match self.layout {
+                Some((ref layout, _)) => place_content(layout, html).unwrap_or_else(|| html.clone()),
+                None => html.clone(),
+            };
+            template.html_body = Some(html);
+        }
+        template
+    }
+
+    /// Render a template with `variables` and the branding, which takes
+    /// precedence
+    pub fn render(&self, template: &str, variables: &TemplateVariables) -> Result<String> {
+        let branding = self.branding.variables();
+        let mut data: HashMap<&str, &str> = variables.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        for (name, value) in &branding {
+            data.insert(name, value);
+        }
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        handlebars
+            .render_template(template, &data)
+            .map_err(|e| Error::notification_error(format!("Failed to render email template: {}", e)))
+    }
+
+    /// Problems with the layout and overrides: parts that don't compile,
+    /// unknown variables, and required variables missing from the email
+    pub fn validate(&self) -> Vec<TemplateIssue> {
+        let mut issues = self.issues.clone();
+
+        let mut overridden: Vec<_> = self.overrides.iter().collect();
+        overridden.sort_by_key(|(id, _)| **id);
+        for (id, template_override) in overridden {
+            let Some(template_type) = catalog::template_type(id) else {
+                continue;
+            };
+            let mut problems = Vec::new();
+
+            let own_parts = [&template_override.subject, &template_override.text, &template_override.html];
+            let own_parts: Vec<&str> = own_parts.into_iter().flatten().map(String::as_str).collect();
+            if let Err(e) = catalog::validate_template(id, &own_parts) {
+                problems.push(message(e));
+            }
+
+            // Required variables may come from the parts left to the built-in template
+            match NotificationTemplate::builtin(template_type.template_id) {
+                Ok(builtin) => {
+                    let template = self.apply(builtin);
+                    let html = template.html_body.clone().unwrap_or_default();
+                    let parts = [template.subject.as_str(), template.body.as_str(), html.as_str()];
+                    let missing = catalog::missing_required(template_type, &parts);
+                    if !missing.is_empty() {
+                        problems.push(format!("Missing required variables: {}", missing.join(", ")));
+                    }
+
+                    let samples = catalog::sample_variables(id).unwrap_or_default();
+                    if let Some(e) = parts.iter().find_map(|part| self.render(part, &samples).err()) {
+                        problems.push(message(e));
+                    }
+                }
+                Err(e) => problems.push(message(e)),
+            }
+
+            issues.extend(problems.into_iter().map(|message| TemplateIssue {
+                template: id.to_string(),
+                source: template_override.source,
+                message,
+            }));
+        }
+        issues
+    }
+}
+
+/// Check that a layout places the content and only uses the branding
+pub fn check_layout(layout: &str) -> Result<()> {
+    if !CONTENT_TAGS.iter().any(|tag| layout.contains(tag)) {
+        return Err(Error::validation("The layout must place the email content with {{ content }}"));
+    }
+    let branding = EmailBranding::default().variables();
+    let unknown: Vec<String> = catalog::placeholders(layout)
+        .into_iter()
+        .filter(|name| name.as_str() != "content" && !branding.iter().any(|(b, _)| name.as_str() == *b))
+        .collect();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::validation(format!(
+            "Unknown layout variables: {} (layouts see the branding and content)",
+            unknown.join(", ")
+        )))
+    }
+}
+
+/// Build the theme from the email config and the stored templates
+pub async fn load(config: &EmailConfig, templates: Option<&dyn NotificationTemplateRepository>) -> Result<EmailTheme> {
+    let mut theme = EmailTheme::new(config.branding.clone());
+    if let Some(ref dir) = config.templates_dir {
+        theme = theme.with_directory(dir)?;
+    }
+    if let Some(templates) = templates {
+        theme = theme.with_records(&templates.list().await?);
+    }
+    Ok(theme)
+}
+
+/// Install the theme `NotificationTemplate::load` applies
+pub fn install(theme: EmailTheme) {
+    *INSTALLED.write().unwrap() = Some(Arc::new(theme));
+}
+
+/// The installed theme; the default branding without overrides until one
+/// is installed
+pub fn current() -> Arc<EmailTheme> {
+    INSTALLED.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Template type an override file is for
+fn file_template_type(file: &str) -> Option<&'static TemplateType> {
+    let id = file
+        .strip_suffix(".subject.txt")
+        .or_else(|| file.strip_suffix(".txt"))
+        .or_else(|| file.strip_suffix(".html"))?;
+    catalog::template_type(id)
+}
+
+fn place_content(layout: &str, content: &str) -> Option<String> {
+    CONTENT_TAGS
+        .iter()
+        .find(|tag| layout.contains(*tag))
+        .map(|tag| layout.replacen(tag, content, 1))
+}
+
+fn compile(template: &str) -> Result<()> {
+    Template::compile(template)
+        .map(|_| ())
+        .map_err(|e| Error::validation(format!("Invalid template: {}", e)))
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    (!text.trim().is_empty()).then(|| text.to_string())
+}
+
+/// An error's message without the error kind
+fn message(e: Error) -> String {
+    match e {
+        Error::Validation(message) => message,
+        e => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn record(name: &str, subject: &str, html: Option<&str>) -> NotificationTemplateRecord {
+        NotificationTemplateRecord {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            channel: NotificationChannel::Email,
+            subject_template: subject.to_string(),
+            body_template: String::new(),
+            html_template: html.map(str::to_string),
+            variables: serde_json::json!([]),
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn branding() -> EmailBranding {
+        EmailBranding {
+            company_name: "Acme".to_string(),
+            logo_url: Some("https://acme.test/logo.png".to_string()),
+            footer_text: Some("1 Main St, Springfield".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_override_in_layout_with_builtin_fallback() {
+        let theme = EmailTheme::new(branding()).with_records(&[
+            record(LAYOUT, "", Some("<body><img src=\"{{ logo_url }}\">{{ content }}<p>{{ footer_text }}</p></body>")),
+            record("password_reset", "Reset your {{ company_name }} password", Some("<a href=\"{{reset_url}}\">Reset</a>")),
+        ]);
+        assert!(theme.validate().is_empty());
+
+        let template = theme.apply(NotificationTemplate::builtin("password_reset_html").unwrap());
+        let mut vars = TemplateVariables::new();
+        vars.insert("reset_url", "https://acme.test/reset");
+        vars.insert("company_name", "R Commerce");
+
+        assert_eq!(theme.render(&template.subject, &vars).unwrap(), "Reset your Acme password");
+        assert_eq!(
+            theme.render(template.html_body.as_deref().unwrap(), &vars).unwrap(),
+            "<body><img src=\"https://acme.test/logo.png\"><a href=\"https://acme.test/reset\">Reset</a><p>1 Main St, Springfield</p></body>"
+        );
+        // The stored template has no text body, so the built-in one is kept
+        assert_eq!(template.body, NotificationTemplate::builtin("password_reset_html").unwrap().body);
+    }
+
+    #[test]
+    fn test_builtin_templates_use_branding() {
+        let theme = EmailTheme::new(branding());
+        let template = NotificationTemplate::builtin("welcome_html").unwrap();
+        let html = theme.render(template.html_body.as_deref().unwrap(), &TemplateVariables::new()).unwrap();
+        assert!(html.contains("<img src=\"https://acme.test/logo.png\" alt=\"Acme\""));
+        assert!(html.contains("1 Main St, Springfield"));
+        assert!(html.contains("color: #EB4F27;"));
+        assert!(!html.contains("RCOMMERCE"));
+
+        let html = EmailTheme::default()
+            .render(template.html_body.as_deref().unwrap(), &TemplateVariables::new())
+            .unwrap();
+        assert!(html.contains("RCOMMERCE"));
+    }
+
+    #[test]
+    fn test_invalid_overrides_are_reported() {
+        let theme = EmailTheme::default().with_records(&[
+            record("order_shipped", "Shipped", Some("{{#if tracking_url}}Track it")),
+            record("welcome", "Welcome {{ customer_name }}", Some("<p>Hello {{ nickname }}</p>")),
+            record("shipping_notification", "Shipped", None),
+        ]);
+
+        // The override that doesn't compile is left out
+        assert!(!theme.overrides().contains_key("order_shipped"));
+        let issues = theme.validate();
+        let for_template = |name: &str| issues.iter().filter(|i| i.template == name).count();
+        assert_eq!(for_template("order_shipped"), 1);
+        assert_eq!(for_template("shipping_notification"), 1);
+        // Unknown `nickname`, and no `login_url` anywhere in the email
+        assert_eq!(for_template("welcome"), 2);
+        assert!(issues.iter().any(|i| i.message.contains("login_url")));
+    }
+
+    #[test]
+    fn test_directory_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("order_cancelled.subject.txt"), "Cancelled: {{ order_number }}\n").unwrap();
+        std::fs::write(dir.path().join("layout.html"), "<main>{{ order_number }}</main>").unwrap();
+        std::fs::write(dir.path().join("order_canceled.html"), "<p>typo</p>").unwrap();
+        std::fs::write(dir.path().join("README.md"), "Email templates").unwrap();
+
+        let theme = EmailTheme::default().with_directory(dir.path()).unwrap();
+        let template = theme.apply(NotificationTemplate::builtin("order_cancelled_html").unwrap());
+        assert_eq!(template.subject, "Cancelled: {{ order_number }}");
+
+        let issues = theme.validate();
+        let templates: Vec<&str> = issues.iter().map(|i| i.template.as_str()).collect();
+        assert_eq!(templates, vec![LAYOUT, "order_canceled.html"]);
+        assert!(issues.iter().all(|i| i.source == OverrideSource::Directory));
+    }
+}
@@ -74,6 +74,10 @@ pub enum DeliveryStatus {
     Delivered,
     Failed,
     Bounced,
+    /// The recipient reported the email as spam
+    Complained,
+    /// Not sent because the recipient opted out of it
+    Suppressed,
 }
 
 /// A single delivery attempt
@@ -0,0 +1,271 @@
+//! Alert rule evaluation
+//!
+//! Each check measures a rule's metric over its window and moves its state
+//! along: a breach starts (`breached_since`), lasts `for_secs` and fires,
+//! is repeated every `repeat_after_secs` while it lasts and resolves once
+//! the metric is back within the threshold. Rates measured from fewer than
+//! `min_samples` events leave the state as it was.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::config::MetricAlertRule;
+
+/// Where an alert rule stands, kept between checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct MetricAlertState {
+    pub rule_name: String,
+    /// When the current breach began; None while within the threshold
+    pub breached_since: Option<DateTime<Utc>>,
+    /// Staff were told about the current breach
+    pub firing: bool,
+    pub last_value: Option<f64>,
+    pub last_notified_at: Option<DateTime<Utc>>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl MetricAlertState {
+    pub fn new(rule_name: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            rule_name: rule_name.to_string(),
+            breached_since: None,
+            firing: false,
+            last_value: None,
+            last_notified_at: None,
+            checked_at: now,
+        }
+    }
+}
+
+/// What a check of an alert rule calls for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertTransition {
+    /// Nothing to tell
+    None,
+    /// The threshold has been breached for `for_secs`
+    Fire,
+    /// Still breached `repeat_after_secs` after the last notification
+    Remind,
+    /// Back within the threshold
+    Resolve,
+}
+
+/// Whether a value breaches a rule's threshold
+pub fn breaches(rule: &MetricAlertRule, value: f64) -> bool {
+    match (rule.above, rule.below) {
+        (Some(above), _) => value > above,
+        (None, Some(below)) => value < below,
+        (None, None) => false,
+    }
+}
+
+/// Check a rule against a measurement (`BusinessMetrics::value`)
+pub fn evaluate(
+    rule: &MetricAlertRule,
+    (value, samples): (Option<f64>, i64),
+    state: MetricAlertState,
+    repeat_after_secs: u64,
+    now: DateTime<Utc>,
+) -> (MetricAlertState, AlertTransition) {
+    let mut state = MetricAlertState { checked_at: now, ..state };
+    let value = match value {
+        Some(value) if !rule.metric.is_percentage() || samples >= rule.min_samples => value,
+        _ => return (state, AlertTransition::None),
+    };
+    state.last_value = Some(value);
+
+    if !breaches(rule, value) {
+        state.breached_since = None;
+        if state.firing {
+            state.firing = false;
+            state.last_notified_at = Some(now);
+            return (state, AlertTransition::Resolve);
+        }
+        return (state, AlertTransition::None);
+    }
+
+    let since = *state.breached_since.get_or_insert(now);
+    let transition = if !state.firing {
+        if now - since < Duration::seconds(rule.for_secs as i64) {
+            return (state, AlertTransition::None);
+        }
+        state.firing = true;
+        AlertTransition::Fire
+    } else {
+        let reminder_due = repeat_after_secs > 0
+            && state
+                .last_notified_at
+                .map_or(true, |at| now - at >= Duration::seconds(repeat_after_secs as i64));
+        if !reminder_due {
+            return (state, AlertTransition::None);
+        }
+        AlertTransition::Remind
+    };
+    state.last_notified_at = Some(now);
+    (state, transition)
+}
+
+/// The notification subject and text for a transition
+pub fn alert_message(rule: &MetricAlertRule, transition: AlertTransition, value: f64) -> (String, String) {
+    let metric = rule.metric;
+    let threshold = match (rule.above, rule.below) {
+        (Some(above), _) => format!("above {}", metric.format_value(above)),
+        (None, Some(below)) => format!("below {}", metric.format_value(below)),
+        (None, None) => String::new(),
+    };
+    let measured = format!(
+        "{} is {} over the last {}",
+        metric.label(),
+        metric.format_value(value),
+        format_duration(rule.window_secs)
+    );
+
+    match transition {
+        AlertTransition::Resolve => (
+            format!("[RESOLVED] {}", rule.name),
+            format!("Alert {} resolved: {} (alerting {}).", rule.name, measured, threshold),
+        ),
+        AlertTransition::Remind => (
+            format!("[FIRING] {} (still)", rule.name),
+            format!("Alert {} is still firing: {} ({}).", rule.name, measured, threshold),
+        ),
+        _ => {
+            let lasting = if rule.for_secs > 0 {
+                format!(" for {}", format_duration(rule.for_secs))
+            } else {
+                String::new()
+            };
+            (
+                format!("[FIRING] {}", rule.name),
+                format!("Alert {} is firing: {} ({}{}).", rule.name, measured, threshold, lasting),
+            )
+        }
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    let (n, unit) = if secs % 3600 == 0 {
+        (secs / 3600, "hour")
+    } else if secs % 60 == 0 {
+        (secs / 60, "minute")
+    } else {
+        (secs, "second")
+    };
+    if n == 1 {
+        unit.to_string()
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::BusinessMetric;
+
+    fn rule(above: Option<f64>, below: Option<f64>, for_secs: u64) -> MetricAlertRule {
+        MetricAlertRule {
+            name: "payment_failures".to_string(),
+            metric: BusinessMetric::PaymentFailureRate,
+            above,
+            below,
+            window_secs: 300,
+            for_secs,
+            min_samples: 10,
+        }
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_fires_after_for_secs_and_resolves() {
+        let rule = rule(Some(10.0), None, 300);
+        let state = MetricAlertState::new(&rule.name, at(0));
+
+        // Breached, but not for 5 minutes yet
+        let (state, transition) = evaluate(&rule, (Some(14.0), 50), state, 3600, at(0));
+        assert_eq!(transition, AlertTransition::None);
+        assert_eq!(state.breached_since, Some(at(0)));
+        let (state, transition) = evaluate(&rule, (Some(12.0), 50), state, 3600, at(240));
+        assert_eq!(transition, AlertTransition::None);
+
+        let (state, transition) = evaluate(&rule, (Some(15.0), 50), state, 3600, at(300));
+        assert_eq!(transition, AlertTransition::Fire);
+        assert!(state.firing);
+
+        // No repeat until repeat_after_secs
+        let (state, transition) = evaluate(&rule, (Some(15.0), 50), state, 3600, at(360));
+        assert_eq!(transition, AlertTransition::None);
+        let (state, transition) = evaluate(&rule, (Some(15.0), 50), state, 3600, at(3900));
+        assert_eq!(transition, AlertTransition::Remind);
+
+        let (state, transition) = evaluate(&rule, (Some(4.0), 50), state, 3600, at(3960));
+        assert_eq!(transition, AlertTransition::Resolve);
+        assert!(!state.firing);
+        assert_eq!(state.breached_since, None);
+    }
+
+    #[test]
+    fn test_breach_must_last() {
+        let rule = rule(Some(10.0), None, 300);
+        let state = MetricAlertState::new(&rule.name, at(0));
+
+        let (state, _) = evaluate(&rule, (Some(14.0), 50), state, 0, at(0));
+        // A check within the threshold restarts the breach
+        let (state, _) = evaluate(&rule, (Some(8.0), 50), state, 0, at(120));
+        let (state, transition) = evaluate(&rule, (Some(14.0), 50), state, 0, at(240));
+        assert_eq!(transition, AlertTransition::None);
+        assert_eq!(state.breached_since, Some(at(240)));
+    }
+
+    #[test]
+    fn test_too_few_samples() {
+        let rule = rule(Some(10.0), None, 0);
+        let state = MetricAlertState::new(&rule.name, at(0));
+
+        // 1 of 2 payments failed: not enough to tell
+        let (state, transition) = evaluate(&rule, (Some(50.0), 2), state, 0, at(0));
+        assert_eq!(transition, AlertTransition::None);
+        assert_eq!(state.breached_since, None);
+        let (_, transition) = evaluate(&rule, (None, 0), state, 0, at(60));
+        assert_eq!(transition, AlertTransition::None);
+    }
+
+    #[test]
+    fn test_below_threshold() {
+        let rule = MetricAlertRule {
+            name: "no_orders".to_string(),
+            metric: BusinessMetric::OrdersPerMinute,
+            ..rule(None, Some(0.5), 0)
+        };
+        let state = MetricAlertState::new(&rule.name, at(0));
+
+        // min_samples does not apply to order rates
+        let (state, transition) = evaluate(&rule, (Some(0.0), 0), state, 0, at(0));
+        assert_eq!(transition, AlertTransition::Fire);
+        let (_, transition) = evaluate(&rule, (Some(0.0), 0), state, 0, at(60));
+        assert_eq!(transition, AlertTransition::None);
+    }
+
+    #[test]
+    fn test_alert_message() {
+        let rule = rule(Some(10.0), None, 300);
+
+        let (subject, text) = alert_message(&rule, AlertTransition::Fire, 14.3);
+        assert_eq!(subject, "[FIRING] payment_failures");
+        assert_eq!(
+            text,
+            "Alert payment_failures is firing: payment failure rate is 14.3% over the last 5 minutes (above 10.0% for 5 minutes)."
+        );
+
+        let (subject, text) = alert_message(&rule, AlertTransition::Resolve, 4.0);
+        assert_eq!(subject, "[RESOLVED] payment_failures");
+        assert_eq!(
+            text,
+            "Alert payment_failures resolved: payment failure rate is 4.0% over the last 5 minutes (alerting above 10.0%)."
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Anomaly detection with a rolling z-score
+//!
+//! A rate (payment failures, refunds, 5xx responses) is measured over the
+//! latest window and over the windows before it. The latest rate is an
+//! anomaly when it is `z_threshold` standard deviations above the mean of
+//! the earlier ones and at least `min_rate`. Windows with fewer than
+//! `min_samples` events are left out, and the check is skipped without
+//! enough history.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+use crate::config::AnomalyDetectionConfig;
+use crate::models::AnomalyMetric;
+
+/// Baseline windows needed before a rate is checked
+pub const MIN_BASELINE_BUCKETS: usize = 8;
+
+/// Floor under the baseline's standard deviation, in percentage points, so
+/// a perfectly flat history does not make every blip an anomaly
+pub const MIN_STDDEV: f64 = 0.5;
+
+/// Events of interest out of all events in a window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateSample {
+    /// Failed payments, refunds or 5xx responses
+    pub events: i64,
+    /// Finished payments, orders placed or requests
+    pub total: i64,
+}
+
+impl RateSample {
+    /// The rate as a percentage
+    pub fn rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.events as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// The latest rate against its baseline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnomalyCheck {
+    pub metric: AnomalyMetric,
+    pub rate: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub z_score: f64,
+    /// Events the latest rate was measured from
+    pub samples: i64,
+    pub anomalous: bool,
+}
+
+impl AnomalyCheck {
+    /// One line for incidents and notifications
+    pub fn summary(&self) -> String {
+        format!(
+            "{} is {:.1}% against a usual {:.1}% ± {:.1}% (z = {:.1}, {} samples)",
+            self.metric.title(),
+            self.rate,
+            self.mean,
+            self.stddev,
+            self.z_score,
+            self.samples
+        )
+    }
+}
+
+/// Check the latest window of `series` (newest first) against the rest;
+/// None without enough events or history to tell
+pub fn check(metric: AnomalyMetric, series: &[RateSample], config: &AnomalyDetectionConfig) -> Option<AnomalyCheck> {
+    let (latest, history) = series.split_first()?;
+    if latest.total < config.min_samples {
+        return None;
+    }
+    let baseline: Vec<f64> = history
+        .iter()
+        .filter(|sample| sample.total >= config.min_samples)
+        .map(RateSample::rate)
+        .collect();
+    if baseline.len() < MIN_BASELINE_BUCKETS {
+        return None;
+    }
+
+    let count = baseline.len() as f64;
+    let mean = baseline.iter().sum::<f64>() / count;
+    let variance = baseline.iter().map(|rate| (rate - mean).powi(2)).sum::<f64>() / count;
+    let stddev = variance.sqrt().max(MIN_STDDEV);
+    let rate = latest.rate();
+    let z_score = (rate - mean) / stddev;
+
+    Some(AnomalyCheck {
+        metric,
+        rate,
+        mean,
+        stddev,
+        z_score,
+        samples: latest.total,
+        anomalous: z_score >= config.z_threshold && rate >= config.min_rate,
+    })
+}
+
+/// Requests and 5xx responses an instance served since it last flushed
+/// them to `request_stats`
+#[derive(Debug, Default)]
+pub struct RequestCounter {
+    requests: AtomicU64,
+    server_errors: AtomicU64,
+}
+
+impl RequestCounter {
+    pub fn record(&self, status: u16) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if (500..600).contains(&status) {
+            self.server_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The counts so far (requests, 5xx), starting over from zero
+    pub fn take(&self) -> (i64, i64) {
+        let requests = self.requests.swap(0, Ordering::Relaxed);
+        let server_errors = self.server_errors.swap(0, Ordering::Relaxed);
+        (requests as i64, server_errors.min(requests) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(events: i64, total: i64) -> RateSample {
+        RateSample { events, total }
+    }
+
+    fn config() -> AnomalyDetectionConfig {
+        AnomalyDetectionConfig { enabled: true, ..Default::default() }
+    }
+
+    /// A steady 2-4% failure rate over 12 windows
+    fn history() -> Vec<RateSample> {
+        [2, 3, 4, 3, 2, 3, 4, 3, 2, 3, 4, 3].iter().map(|&failed| sample(failed, 100)).collect()
+    }
+
+    #[test]
+    fn test_spike_is_anomalous() {
+        let series: Vec<RateSample> = std::iter::once(sample(25, 100)).chain(history()).collect();
+        let check = check(AnomalyMetric::PaymentFailureRate, &series, &config()).unwrap();
+
+        assert_eq!(check.rate, 25.0);
+        assert_eq!(check.mean, 3.0);
+        assert!(check.z_score > 3.0);
+        assert!(check.anomalous);
+        assert!(check.summary().starts_with("Payment failure rate is 25.0% against a usual 3.0%"));
+    }
+
+    #[test]
+    fn test_normal_rate() {
+        let series: Vec<RateSample> = std::iter::once(sample(4, 100)).chain(history()).collect();
+        let check = check(AnomalyMetric::PaymentFailureRate, &series, &config()).unwrap();
+        assert!(!check.anomalous);
+    }
+
+    #[test]
+    fn test_flat_history_and_min_rate() {
+        let flat: Vec<RateSample> = std::iter::repeat(sample(0, 100)).take(12).collect();
+
+        // 1% is many deviations above a flat 0%, but below min_rate
+        let series: Vec<RateSample> = std::iter::once(sample(1, 100)).chain(flat.clone()).collect();
+        let small = check(AnomalyMetric::ServerErrorRate, &series, &config()).unwrap();
+        assert_eq!(small.stddev, MIN_STDDEV);
+        assert!(!small.anomalous);
+
+        let series: Vec<RateSample> = std::iter::once(sample(5, 100)).chain(flat).collect();
+        assert!(check(AnomalyMetric::ServerErrorRate, &series, &config()).unwrap().anomalous);
+    }
+
+    #[test]
+    fn test_not_enough_data() {
+        // Too few events in the latest window
+        let series: Vec<RateSample> = std::iter::once(sample(5, 10)).chain(history()).collect();
+        assert!(check(AnomalyMetric::RefundRate, &series, &config()).is_none());
+
+        // Too little history with enough events
+        let mut series: Vec<RateSample> = std::iter::once(sample(25, 100)).chain(history()).collect();
+        for window in series.iter_mut().skip(6) {
+            window.total = 5;
+        }
+        assert!(check(AnomalyMetric::RefundRate, &series, &config()).is_none());
+    }
+
+    #[test]
+    fn test_request_counter() {
+        let counter = RequestCounter::default();
+        counter.record(200);
+        counter.record(502);
+        counter.record(404);
+        assert_eq!(counter.take(), (3, 1));
+        assert_eq!(counter.take(), (0, 0));
+    }
+}
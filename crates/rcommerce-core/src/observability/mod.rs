@@ -0,0 +1,262 @@
+//! Business metrics and alerts
+//!
+//! Business KPIs are counted from orders, payments and carts over a recent
+//! window ([`BusinessMetrics`]), so every instance reports the same numbers.
+//! `GET /metrics` serves them in the Prometheus text format
+//! ([`BusinessMetrics::render_prometheus`]) with a fixed, low-cardinality
+//! label set:
+//!
+//! - `rcommerce_orders_per_minute` - orders placed per minute
+//! - `rcommerce_payments{outcome}` - payments that succeeded or failed
+//! - `rcommerce_payment_failure_ratio` - failed / all finished payments
+//! - `rcommerce_checkout_funnel{stage}` - carts created, checkouts started
+//!   (a cart with an email or shipping address), orders placed, orders paid
+//! - `rcommerce_checkout_conversion_ratio` - orders placed / carts created
+//! - `rcommerce_alert_firing{alert}` - 1 while an alert rule is firing
+//!
+//! Alert rules (`[[observability.alerts.rules]]`) are checked by the
+//! `metric_alerts` recurring job ([`MetricsService::check_alerts`]).
+//!
+//! The `anomaly_detection` job ([`MetricsService::check_anomalies`]) opens
+//! an incident when the payment failure, refund or 5xx rate jumps well
+//! above its recent history (see [`anomaly`]).
+
+pub mod alerts;
+pub mod anomaly;
+pub mod service;
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+pub use alerts::{AlertTransition, MetricAlertState};
+pub use anomaly::{AnomalyCheck, RateSample, RequestCounter};
+pub use service::{AnomalyJob, AnomalyReport, MetricAlertJob, MetricAlertReport, MetricsService};
+
+/// A business metric alert rules can watch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusinessMetric {
+    /// Orders placed per minute
+    OrdersPerMinute,
+    /// Percentage of finished payments that failed
+    PaymentFailureRate,
+    /// Orders placed per 100 carts created
+    CheckoutConversionRate,
+}
+
+impl BusinessMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BusinessMetric::OrdersPerMinute => "orders_per_minute",
+            BusinessMetric::PaymentFailureRate => "payment_failure_rate",
+            BusinessMetric::CheckoutConversionRate => "checkout_conversion_rate",
+        }
+    }
+
+    /// Rates are percentages (0 to 100)
+    pub fn is_percentage(&self) -> bool {
+        !matches!(self, BusinessMetric::OrdersPerMinute)
+    }
+
+    pub fn unit(&self) -> &'static str {
+        if self.is_percentage() {
+            "a percentage"
+        } else {
+            "orders per minute"
+        }
+    }
+
+    /// A value as written in notifications
+    pub fn format_value(&self, value: f64) -> String {
+        if self.is_percentage() {
+            format!("{:.1}%", value)
+        } else {
+            format!("{:.2} orders/min", value)
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            BusinessMetric::OrdersPerMinute => "orders per minute",
+            BusinessMetric::PaymentFailureRate => "payment failure rate",
+            BusinessMetric::CheckoutConversionRate => "checkout conversion rate",
+        }
+    }
+}
+
+/// Orders, payments and carts counted over a window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BusinessMetrics {
+    pub window_secs: u64,
+    /// Carts created
+    pub carts_created: i64,
+    /// Carts created that got an email or a shipping address
+    pub checkouts_started: i64,
+    /// Orders placed
+    pub orders_placed: i64,
+    /// Orders placed that are paid
+    pub orders_paid: i64,
+    /// Payments authorized or paid
+    pub payments_succeeded: i64,
+    pub payments_failed: i64,
+}
+
+impl BusinessMetrics {
+    pub fn orders_per_minute(&self) -> f64 {
+        self.orders_placed as f64 * 60.0 / self.window_secs.max(1) as f64
+    }
+
+    /// Failed / finished payments; None without payments
+    pub fn payment_failure_ratio(&self) -> Option<f64> {
+        let payments = self.payments_succeeded + self.payments_failed;
+        (payments > 0).then(|| self.payments_failed as f64 / payments as f64)
+    }
+
+    /// Orders placed / carts created; None without carts
+    pub fn checkout_conversion_ratio(&self) -> Option<f64> {
+        (self.carts_created > 0).then(|| (self.orders_placed as f64 / self.carts_created as f64).min(1.0))
+    }
+
+    /// A metric's value, with the events it was measured from
+    pub fn value(&self, metric: BusinessMetric) -> (Option<f64>, i64) {
+        match metric {
+            BusinessMetric::OrdersPerMinute => (Some(self.orders_per_minute()), self.orders_placed),
+            BusinessMetric::PaymentFailureRate => (
+                self.payment_failure_ratio().map(|ratio| ratio * 100.0),
+                self.payments_succeeded + self.payments_failed,
+            ),
+            BusinessMetric::CheckoutConversionRate => (
+                self.checkout_conversion_ratio().map(|ratio| ratio * 100.0),
+                self.carts_created,
+            ),
+        }
+    }
+
+    /// The metrics in the Prometheus text format, with whether each alert
+    /// rule is firing
+    pub fn render_prometheus(&self, alerts: &[(String, bool)]) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, samples: &[(&str, f64)]| {
+            let _ = writeln!(out, "# HELP rcommerce_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE rcommerce_{} gauge", name);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "rcommerce_{}{} {}", name, labels, value);
+            }
+        };
+
+        gauge(
+            "metrics_window_seconds",
+            "Seconds of orders, payments and carts the business metrics cover",
+            &[("", self.window_secs as f64)],
+        );
+        gauge(
+            "orders_per_minute",
+            "Orders placed per minute over the window",
+            &[("", self.orders_per_minute())],
+        );
+        gauge(
+            "payments",
+            "Payments finished in the window, by outcome",
+            &[
+                ("{outcome=\"succeeded\"}", self.payments_succeeded as f64),
+                ("{outcome=\"failed\"}", self.payments_failed as f64),
+            ],
+        );
+        gauge(
+            "payment_failure_ratio",
+            "Failed share of payments finished in the window",
+            &[("", self.payment_failure_ratio().unwrap_or(0.0))],
+        );
+        gauge(
+            "checkout_funnel",
+            "Checkout funnel counts in the window, by stage",
+            &[
+                ("{stage=\"cart_created\"}", self.carts_created as f64),
+                ("{stage=\"checkout_started\"}", self.checkouts_started as f64),
+                ("{stage=\"order_placed\"}", self.orders_placed as f64),
+                ("{stage=\"order_paid\"}", self.orders_paid as f64),
+            ],
+        );
+        gauge(
+            "checkout_conversion_ratio",
+            "Orders placed per cart created in the window",
+            &[("", self.checkout_conversion_ratio().unwrap_or(0.0))],
+        );
+
+        if !alerts.is_empty() {
+            let samples: Vec<(String, f64)> = alerts
+                .iter()
+                .map(|(name, firing)| (format!("{{alert=\"{}\"}}", escape_label(name)), f64::from(u8::from(*firing))))
+                .collect();
+            let samples: Vec<(&str, f64)> = samples.iter().map(|(labels, value)| (labels.as_str(), *value)).collect();
+            gauge("alert_firing", "1 while a business metric alert is firing", &samples);
+        }
+        out
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Compare a presented `/metrics` token without leaking where it differs
+pub fn token_matches(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> BusinessMetrics {
+        BusinessMetrics {
+            window_secs: 300,
+            carts_created: 200,
+            checkouts_started: 80,
+            orders_placed: 30,
+            orders_paid: 27,
+            payments_succeeded: 27,
+            payments_failed: 3,
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        let metrics = metrics();
+        assert_eq!(metrics.value(BusinessMetric::OrdersPerMinute), (Some(6.0), 30));
+        assert_eq!(metrics.value(BusinessMetric::PaymentFailureRate), (Some(10.0), 30));
+        assert_eq!(metrics.value(BusinessMetric::CheckoutConversionRate), (Some(15.0), 200));
+
+        let quiet = BusinessMetrics { window_secs: 300, ..Default::default() };
+        assert_eq!(quiet.value(BusinessMetric::OrdersPerMinute), (Some(0.0), 0));
+        assert_eq!(quiet.value(BusinessMetric::PaymentFailureRate), (None, 0));
+        assert_eq!(quiet.value(BusinessMetric::CheckoutConversionRate), (None, 0));
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let text = metrics().render_prometheus(&[("payment_failures".to_string(), true)]);
+
+        assert!(text.contains("# TYPE rcommerce_orders_per_minute gauge\nrcommerce_orders_per_minute 6\n"));
+        assert!(text.contains("rcommerce_payments{outcome=\"failed\"} 3\n"));
+        assert!(text.contains("rcommerce_payment_failure_ratio 0.1\n"));
+        assert!(text.contains("rcommerce_checkout_funnel{stage=\"checkout_started\"} 80\n"));
+        assert!(text.contains("rcommerce_checkout_conversion_ratio 0.15\n"));
+        assert!(text.contains("rcommerce_alert_firing{alert=\"payment_failures\"} 1\n"));
+        assert!(!metrics().render_prometheus(&[]).contains("alert_firing"));
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("s3cret-metrics-token", "s3cret-metrics-token"));
+        assert!(!token_matches("s3cret-metrics-token", "s3cret-metrics-tokem"));
+        assert!(!token_matches("s3cret-metrics-token", "s3cret"));
+    }
+}
@@ -0,0 +1,439 @@
+//! Business metrics for `/metrics`, the `metric_alerts` job and the
+//! `anomaly_detection` job
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::alerts::{alert_message, evaluate, AlertTransition, MetricAlertState};
+use super::anomaly::{self, AnomalyCheck, RequestCounter};
+use super::BusinessMetrics;
+use crate::config::ObservabilityConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::models::{Incident, IncidentStatus};
+use crate::notification::service::NotificationFactory;
+use crate::notification::{Notification, Recipient};
+use crate::repository::{MetricsRepository, NotificationRepository};
+use crate::{Error, Result};
+
+/// Outcome of a run of the `metric_alerts` job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricAlertReport {
+    /// Rules checked
+    pub rules: usize,
+    /// Alerts that started firing
+    pub fired: usize,
+    /// Alerts that resolved
+    pub resolved: usize,
+    /// Notifications that could not be sent
+    pub failed: usize,
+}
+
+impl std::fmt::Display for MetricAlertReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checked {} alert rules", self.rules)?;
+        if self.fired > 0 {
+            write!(f, ", {} fired", self.fired)?;
+        }
+        if self.resolved > 0 {
+            write!(f, ", {} resolved", self.resolved)?;
+        }
+        if self.failed > 0 {
+            write!(f, " ({} notifications failed)", self.failed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a run of the `anomaly_detection` job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnomalyReport {
+    /// Rates with enough data to check
+    pub checked: usize,
+    /// Incidents opened
+    pub opened: usize,
+    /// Incidents resolved because the rate is back to normal
+    pub resolved: usize,
+    /// Notifications that could not be sent
+    pub failed: usize,
+}
+
+impl std::fmt::Display for AnomalyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checked {} rates", self.checked)?;
+        if self.opened > 0 {
+            write!(f, ", {} incidents opened", self.opened)?;
+        }
+        if self.resolved > 0 {
+            write!(f, ", {} incidents resolved", self.resolved)?;
+        }
+        if self.failed > 0 {
+            write!(f, " ({} notifications failed)", self.failed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Business metrics, the alert rules on them and anomaly incidents
+pub struct MetricsService<R: MetricsRepository> {
+    repository: R,
+    config: ObservabilityConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+    http: reqwest::Client,
+    requests: RequestCounter,
+}
+
+impl<R: MetricsRepository> MetricsService<R> {
+    pub fn new(repository: R, config: ObservabilityConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.alerts.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self {
+            repository,
+            config,
+            notifications: None,
+            http,
+            requests: RequestCounter::default(),
+        }
+    }
+
+    /// Queue alert emails through the notification queue
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &ObservabilityConfig {
+        &self.config
+    }
+
+    /// Metrics over `metrics_window_secs`
+    pub async fn business_metrics(&self) -> Result<BusinessMetrics> {
+        self.repository.business_metrics(self.config.metrics_window_secs).await
+    }
+
+    /// The `/metrics` page
+    pub async fn render_prometheus(&self) -> Result<String> {
+        let metrics = self.business_metrics().await?;
+        let states: HashMap<String, bool> = self
+            .repository
+            .alert_states()
+            .await?
+            .into_iter()
+            .map(|state| (state.rule_name, state.firing))
+            .collect();
+        let alerts: Vec<(String, bool)> = self
+            .config
+            .alerts
+            .rules
+            .iter()
+            .map(|rule| (rule.name.clone(), states.get(&rule.name).copied().unwrap_or(false)))
+            .collect();
+        Ok(metrics.render_prometheus(&alerts))
+    }
+
+    /// Check every alert rule, notifying staff of alerts that fire, are
+    /// still firing or resolve
+    pub async fn check_alerts(&self) -> Result<MetricAlertReport> {
+        let rules = &self.config.alerts.rules;
+        let mut report = MetricAlertReport { rules: rules.len(), ..Default::default() };
+        if rules.is_empty() {
+            return Ok(report);
+        }
+
+        let now = Utc::now();
+        let mut states: HashMap<String, MetricAlertState> = self
+            .repository
+            .alert_states()
+            .await?
+            .into_iter()
+            .map(|state| (state.rule_name.clone(), state))
+            .collect();
+        // Rules sharing a window share its counts
+        let mut windows: HashMap<u64, BusinessMetrics> = HashMap::new();
+
+        for rule in rules {
+            let metrics = match windows.get(&rule.window_secs) {
+                Some(metrics) => metrics,
+                None => {
+                    let metrics = self.repository.business_metrics(rule.window_secs).await?;
+                    windows.entry(rule.window_secs).or_insert(metrics)
+                }
+            };
+            let measured = metrics.value(rule.metric);
+            let state = states
+                .remove(&rule.name)
+                .unwrap_or_else(|| MetricAlertState::new(&rule.name, now));
+            let (state, transition) = evaluate(rule, measured, state, self.config.alerts.repeat_after_secs, now);
+
+            if transition != AlertTransition::None {
+                match transition {
+                    AlertTransition::Fire => {
+                        report.fired += 1;
+                        tracing::warn!(alert = %rule.name, value = ?state.last_value, "Business metric alert firing");
+                    }
+                    AlertTransition::Resolve => {
+                        report.resolved += 1;
+                        tracing::info!(alert = %rule.name, value = ?state.last_value, "Business metric alert resolved");
+                    }
+                    _ => {}
+                }
+                let value = state.last_value.unwrap_or_default();
+                let (subject, text) = alert_message(rule, transition, value);
+                let resolved = transition == AlertTransition::Resolve;
+                let notified = self
+                    .notify(&subject, &text, |recipient| {
+                        NotificationFactory::metric_alert(&rule.name, &subject, &text, resolved, recipient)
+                    })
+                    .await;
+                if let Err(e) = notified {
+                    tracing::error!(alert = %rule.name, "Failed to send alert notification: {}", e);
+                    report.failed += 1;
+                }
+            }
+            self.repository.save_alert_state(&state).await?;
+        }
+        Ok(report)
+    }
+
+    /// Count a response towards the 5xx rate (while anomaly detection is on)
+    pub fn record_response(&self, status: u16) {
+        if self.config.anomalies.enabled {
+            self.requests.record(status);
+        }
+    }
+
+    /// Add the responses counted since the last flush to `request_stats`
+    pub async fn flush_request_stats(&self) -> Result<()> {
+        let (requests, server_errors) = self.requests.take();
+        if requests == 0 {
+            return Ok(());
+        }
+        self.repository.add_request_stats(Utc::now(), requests, server_errors).await
+    }
+
+    /// Check each watched rate against its history, opening an incident for
+    /// a new anomaly and resolving incidents whose rate is back to normal
+    pub async fn check_anomalies(&self) -> Result<AnomalyReport> {
+        let config = &self.config.anomalies;
+        let mut report = AnomalyReport::default();
+
+        for &metric in &config.metrics {
+            let series = self
+                .repository
+                .rate_series(metric, config.bucket_secs, config.baseline_buckets + 1)
+                .await?;
+            let Some(check) = anomaly::check(metric, &series, config) else {
+                continue;
+            };
+            report.checked += 1;
+
+            let incident = self.repository.unresolved_incident(metric).await?;
+            let notified = match (incident, check.anomalous) {
+                (None, true) => {
+                    let incident = self.repository.open_incident(&check).await?;
+                    report.opened += 1;
+                    tracing::warn!(incident = %incident.id, metric = metric.as_str(), "{}", incident.summary);
+                    self.notify_incident(&incident, &check).await
+                }
+                (Some(incident), true) => {
+                    self.repository.update_incident(incident.id, &check).await?;
+                    Ok(())
+                }
+                (Some(incident), false) => match self.repository.resolve_incident(incident.id, None).await? {
+                    Some(incident) => {
+                        report.resolved += 1;
+                        tracing::info!(incident = %incident.id, metric = metric.as_str(), "Incident resolved: {}", check.summary());
+                        self.notify_incident(&incident, &check).await
+                    }
+                    None => Ok(()),
+                },
+                (None, false) => Ok(()),
+            };
+            if let Err(e) = notified {
+                tracing::error!(metric = metric.as_str(), "Failed to send incident notification: {}", e);
+                report.failed += 1;
+            }
+        }
+
+        // Keep the history the next check needs, and a day to look back on
+        let kept = config.bucket_secs * (config.baseline_buckets as u64 + 1) + 86_400;
+        self.repository
+            .purge_request_stats(Utc::now() - ChronoDuration::seconds(kept as i64))
+            .await?;
+        Ok(report)
+    }
+
+    pub async fn get_incident(&self, id: Uuid) -> Result<Incident> {
+        self.repository
+            .find_incident(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Incident not found"))
+    }
+
+    pub async fn list_incidents(&self, status: Option<IncidentStatus>, limit: i64, offset: i64) -> Result<Vec<Incident>> {
+        self.repository.list_incidents(status, limit, offset).await
+    }
+
+    /// Mark an open incident as being looked into
+    pub async fn acknowledge_incident(&self, id: Uuid, by: Uuid) -> Result<Incident> {
+        let incident = self.get_incident(id).await?;
+        if incident.status != IncidentStatus::Open {
+            return Err(Error::validation("Only open incidents can be acknowledged"));
+        }
+        self.repository
+            .acknowledge_incident(id, by)
+            .await?
+            .ok_or_else(|| Error::validation("Only open incidents can be acknowledged"))
+    }
+
+    /// Resolve an incident by hand; it is reopened by the next check if the
+    /// rate is still anomalous
+    pub async fn resolve_incident(&self, id: Uuid, by: Uuid) -> Result<Incident> {
+        let incident = self.get_incident(id).await?;
+        if incident.status == IncidentStatus::Resolved {
+            return Err(Error::validation("Incident is already resolved"));
+        }
+        self.repository
+            .resolve_incident(id, Some(by))
+            .await?
+            .ok_or_else(|| Error::validation("Incident is already resolved"))
+    }
+
+    async fn notify_incident(&self, incident: &Incident, check: &AnomalyCheck) -> Result<()> {
+        let resolved = incident.status == IncidentStatus::Resolved;
+        let title = check.metric.title();
+        let (subject, text) = if resolved {
+            (
+                format!("[RESOLVED] {} back to normal", title),
+                format!("Incident {} resolved: {}.", incident.id, check.summary()),
+            )
+        } else {
+            (
+                format!("[INCIDENT] {} anomaly", title),
+                format!("Incident {} opened: {}.", incident.id, check.summary()),
+            )
+        };
+        self.notify(&subject, &text, |recipient| {
+            NotificationFactory::incident(incident.id, &subject, &text, resolved, recipient)
+        })
+        .await
+    }
+
+    /// Post to Slack and email staff with `alert_roles`
+    async fn notify(&self, subject: &str, text: &str, notification: impl Fn(Recipient) -> Notification) -> Result<()> {
+        let alerts = &self.config.alerts;
+        let mut result = Ok(());
+
+        if let Some(ref url) = alerts.slack_webhook_url {
+            result = self.post_to_slack(url, &format!("*{}*\n{}", subject, text)).await;
+        }
+        if let Some(ref notifications) = self.notifications {
+            if !alerts.alert_roles.is_empty() {
+                for (_, email) in self.repository.find_staff(&alerts.alert_roles).await? {
+                    notifications.create(&notification(Recipient::email(email, None))).await?;
+                }
+            }
+        }
+        result
+    }
+
+    async fn post_to_slack(&self, url: &str, text: &str) -> Result<()> {
+        let response = self.http.post(url).json(&json!({ "text": text })).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::network(format!("Slack returned {}: {}", status, body)));
+        }
+        Ok(())
+    }
+}
+
+/// The `metric_alerts` recurring job
+pub struct MetricAlertJob<R: MetricsRepository> {
+    metrics: Arc<MetricsService<R>>,
+}
+
+impl<R: MetricsRepository> MetricAlertJob<R> {
+    pub fn new(metrics: Arc<MetricsService<R>>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait]
+impl<R: MetricsRepository + 'static> RecurringJob for MetricAlertJob<R> {
+    fn name(&self) -> &str {
+        "metric_alerts"
+    }
+
+    fn description(&self) -> &str {
+        "Check business metric alert rules and notify staff"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.metrics.config().alerts.check_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.metrics.check_alerts().await?.to_string())
+    }
+}
+
+/// The `anomaly_detection` recurring job
+pub struct AnomalyJob<R: MetricsRepository> {
+    metrics: Arc<MetricsService<R>>,
+}
+
+impl<R: MetricsRepository> AnomalyJob<R> {
+    pub fn new(metrics: Arc<MetricsService<R>>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[async_trait]
+impl<R: MetricsRepository + 'static> RecurringJob for AnomalyJob<R> {
+    fn name(&self) -> &str {
+        "anomaly_detection"
+    }
+
+    fn description(&self) -> &str {
+        "Open incidents for payment failure, refund and 5xx rate spikes"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.metrics.config().anomalies.check_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.metrics.check_anomalies().await?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_display() {
+        let quiet = MetricAlertReport { rules: 2, ..Default::default() };
+        assert_eq!(quiet.to_string(), "checked 2 alert rules");
+
+        let busy = MetricAlertReport { rules: 3, fired: 1, resolved: 1, failed: 1 };
+        assert_eq!(busy.to_string(), "checked 3 alert rules, 1 fired, 1 resolved (1 notifications failed)");
+    }
+
+    #[test]
+    fn test_anomaly_report_display() {
+        let quiet = AnomalyReport { checked: 3, ..Default::default() };
+        assert_eq!(quiet.to_string(), "checked 3 rates");
+
+        let busy = AnomalyReport { checked: 3, opened: 1, resolved: 1, failed: 0 };
+        assert_eq!(busy.to_string(), "checked 3 rates, 1 incidents opened, 1 incidents resolved");
+    }
+}
@@ -1,13 +1,40 @@
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use uuid::Uuid;
 
 use crate::{Result, Error};
+use crate::fx::convert_amount;
+use crate::models::{Currency, CustomerGroupPricing};
 use crate::order::{Order, OrderItem};
 
+/// Conversion between the store's base currency and an order's currency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurrencyConversion {
+    pub base_currency: Currency,
+    pub order_currency: Currency,
+    /// Order currency units per base currency unit
+    pub rate: Decimal,
+}
+
+impl CurrencyConversion {
+    /// Convert a base currency amount into the order currency
+    pub fn to_order(&self, amount: Decimal) -> Decimal {
+        convert_amount(amount, self.rate)
+    }
+    
+    /// Convert an order currency amount into the base currency
+    pub fn to_base(&self, amount: Decimal) -> Decimal {
+        (amount / self.rate).round_dp(2)
+    }
+}
+
 /// Order calculator for totals, tax, shipping, discounts
 pub struct OrderCalculator {
     tax_rate: Decimal,
+    /// Shipping rate in the base currency
     shipping_rate: Decimal,
+    conversion: Option<CurrencyConversion>,
+    customer_group: Option<CustomerGroupPricing>,
 }
 
 impl OrderCalculator {
@@ -15,6 +42,80 @@ impl OrderCalculator {
         Self {
             tax_rate,
             shipping_rate,
+            conversion: None,
+            customer_group: None,
+        }
+    }
+    
+    /// Calculate for an order in a currency other than the base currency
+    pub fn with_conversion(mut self, conversion: CurrencyConversion) -> Self {
+        self.conversion = Some(conversion);
+        self
+    }
+    
+    /// Base to order currency conversion, if the order is in another currency
+    pub fn conversion(&self) -> Option<&CurrencyConversion> {
+        self.conversion.as_ref()
+    }
+    
+    /// Calculate for a customer in a group, with the group's prices for the
+    /// order's products
+    pub fn with_customer_group(mut self, pricing: CustomerGroupPricing) -> Self {
+        self.customer_group = Some(pricing);
+        self
+    }
+    
+    /// The customer's group pricing, if they are in a group
+    pub fn customer_group(&self) -> Option<&CustomerGroupPricing> {
+        self.customer_group.as_ref()
+    }
+    
+    /// Unit price in the order currency: the price list price when there is
+    /// one, otherwise the base price converted at the FX rate
+    pub fn unit_price(&self, base_price: Decimal, list_price: Option<Decimal>) -> Decimal {
+        match (list_price, self.conversion) {
+            (Some(price), _) => price,
+            (None, Some(conversion)) => conversion.to_order(base_price),
+            (None, None) => base_price,
+        }
+    }
+    
+    /// Unit price for the customer: their group's price for the quantity
+    /// when it has one (converted like a base price), otherwise
+    /// `unit_price` less the group's discount
+    pub fn customer_unit_price(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        quantity: i32,
+        base_price: Decimal,
+        list_price: Option<Decimal>,
+    ) -> Decimal {
+        let Some(pricing) = &self.customer_group else {
+            return self.unit_price(base_price, list_price);
+        };
+        match pricing.price_for(product_id, variant_id, quantity) {
+            Some(group_price) => self.unit_price(group_price.price, None),
+            None => pricing.discounted(self.unit_price(base_price, list_price)),
+        }
+    }
+    
+    /// Order totals converted into the base currency for reporting
+    pub fn totals_in_base(&self, totals: &OrderTotals) -> OrderTotals {
+        let Some(conversion) = self.conversion else {
+            return totals.clone();
+        };
+        let subtotal = conversion.to_base(totals.subtotal);
+        let tax_total = conversion.to_base(totals.tax_total);
+        let shipping_total = conversion.to_base(totals.shipping_total);
+        let discount_total = conversion.to_base(totals.discount_total);
+        OrderTotals {
+            subtotal,
+            tax_total,
+            shipping_total,
+            discount_total,
+            // Summed rather than converted, so the base totals stay consistent
+            total: subtotal + tax_total + shipping_total - discount_total,
         }
     }
     
@@ -79,10 +180,15 @@ impl OrderCalculator {
             .filter_map(|item| item.weight.map(|w| w * Decimal::from(item.quantity)))
             .sum();
         
-        if total_weight > dec!(0) {
+        let shipping = if total_weight > dec!(0) {
             self.shipping_rate * total_weight
         } else {
             self.shipping_rate * Decimal::from(items.len())
+        };
+        
+        match self.conversion {
+            Some(conversion) => conversion.to_order(shipping),
+            None => shipping,
         }
     }
     
@@ -293,8 +399,8 @@ impl ShippingCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
     use chrono::Utc;
+    use crate::models::{CustomerGroup, CustomerGroupPrice};
     
     #[test]
     fn test_order_calculator() {
@@ -327,6 +433,79 @@ mod tests {
         assert!(totals.total > dec!(64.00));
     }
     
+    #[test]
+    fn test_order_calculator_currency_conversion() {
+        let calculator = OrderCalculator::new(dec!(0.08), dec!(5.00)).with_conversion(CurrencyConversion {
+            base_currency: Currency::USD,
+            order_currency: Currency::EUR,
+            rate: dec!(0.92),
+        });
+        
+        // Price list prices are used as-is; base prices are converted
+        assert_eq!(calculator.unit_price(dec!(29.99), Some(dec!(27.50))), dec!(27.50));
+        assert_eq!(calculator.unit_price(dec!(29.99), None), dec!(27.59));
+        assert_eq!(calculator.conversion().unwrap().to_order(dec!(5.00)), dec!(4.60));
+        
+        let totals = OrderTotals {
+            subtotal: dec!(92.00),
+            tax_total: dec!(7.36),
+            shipping_total: dec!(4.60),
+            discount_total: dec!(0),
+            total: dec!(103.96),
+        };
+        let base = calculator.totals_in_base(&totals);
+        assert_eq!(base.subtotal, dec!(100.00));
+        assert_eq!(base.shipping_total, dec!(5.00));
+        assert_eq!(base.total, dec!(113.00));
+        assert!(base.is_valid());
+    }
+    
+    #[test]
+    fn test_order_calculator_customer_group() {
+        let product_id = Uuid::new_v4();
+        let pricing = CustomerGroupPricing {
+            group: CustomerGroup {
+                id: Uuid::new_v4(),
+                name: "Wholesale".to_string(),
+                description: None,
+                discount_percent: dec!(10),
+                is_active: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            prices: vec![CustomerGroupPrice {
+                id: Uuid::new_v4(),
+                customer_group_id: Uuid::new_v4(),
+                product_id,
+                variant_id: None,
+                min_quantity: 10,
+                price: dec!(20.00),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }],
+        };
+        
+        let retail = OrderCalculator::new(dec!(0.08), dec!(5.00));
+        assert_eq!(retail.customer_unit_price(product_id, None, 10, dec!(29.99), None), dec!(29.99));
+        
+        let wholesale = OrderCalculator::new(dec!(0.08), dec!(5.00)).with_customer_group(pricing.clone());
+        // Below the group's tier: the group discount
+        assert_eq!(wholesale.customer_unit_price(product_id, None, 9, dec!(29.99), None), dec!(26.99));
+        // From the tier up: the group's price, with no discount on top
+        assert_eq!(wholesale.customer_unit_price(product_id, None, 10, dec!(29.99), None), dec!(20.00));
+        
+        let eur = OrderCalculator::new(dec!(0.08), dec!(5.00))
+            .with_conversion(CurrencyConversion {
+                base_currency: Currency::USD,
+                order_currency: Currency::EUR,
+                rate: dec!(0.92),
+            })
+            .with_customer_group(pricing);
+        // Group prices are converted like base prices; list prices are discounted as-is
+        assert_eq!(eur.customer_unit_price(product_id, None, 10, dec!(29.99), None), dec!(18.40));
+        assert_eq!(eur.customer_unit_price(product_id, None, 1, dec!(29.99), Some(dec!(27.50))), dec!(24.75));
+    }
+    
     #[test]
     fn test_tax_calculator() {
         let calculator = TaxCalculator::new(dec!(0.08));
@@ -29,11 +29,13 @@ pub enum FulfillmentStatus {
     Partial,    // Partial fulfillment (some items shipped)
     Canceled,   // Fulfillment canceled
     Returned,   // Order returned
+    ReadyForPickup, // Waiting at the pickup location
+    PickedUp,   // Collected by the customer
 }
 
 impl FulfillmentStatus {
     pub fn is_terminal(&self) -> bool {
-        matches!(self, FulfillmentStatus::Delivered | FulfillmentStatus::Canceled | FulfillmentStatus::Returned)
+        matches!(self, FulfillmentStatus::Delivered | FulfillmentStatus::Canceled | FulfillmentStatus::Returned | FulfillmentStatus::PickedUp)
     }
     
     pub fn is_shipped(&self) -> bool {
@@ -49,6 +51,8 @@ impl FulfillmentStatus {
             FulfillmentStatus::Partial => "Partial fulfillment",
             FulfillmentStatus::Canceled => "Fulfillment canceled",
             FulfillmentStatus::Returned => "Order returned",
+            FulfillmentStatus::ReadyForPickup => "Ready for pickup",
+            FulfillmentStatus::PickedUp => "Picked up",
         }
     }
 }
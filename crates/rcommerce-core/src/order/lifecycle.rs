@@ -2,12 +2,13 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
+use crate::events::{status_label, DomainEvent, EventBus, PaymentUpdated};
 use crate::Result;
 use super::Order;
 use serde::{Serialize, Deserialize};
 
 /// Order status enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
 #[sqlx(type_name = "order_status", rename_all = "snake_case")]
 pub enum OrderStatus {
     Pending,      // Order created, awaiting payment
@@ -82,60 +83,108 @@ impl OrderStatus {
 }
 
 /// Order event for event sourcing/dispatching
-#[derive(Debug, Clone)]
+///
+/// Recorded in `order_events.payload`; the variant docs are the event
+/// descriptions in the event schema catalog.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum OrderEvent {
+    /// An order was placed
     OrderCreated {
         order_id: Uuid,
         customer_id: Option<Uuid>,
         total: Decimal,
         currency: String,
     },
+    /// An order moved to another status
     OrderStatusChanged {
         order_id: Uuid,
         old_status: OrderStatus,
         new_status: OrderStatus,
         reason: Option<String>,
     },
+    /// A payment for an order succeeded
     PaymentReceived {
         order_id: Uuid,
         payment_id: String,
         amount: Decimal,
         currency: String,
     },
+    /// A payment for an order failed
     PaymentFailed {
         order_id: Uuid,
         payment_id: String,
         error: String,
     },
+    /// An order was handed to a carrier
     OrderShipped {
         order_id: Uuid,
         tracking_number: String,
         carrier: String,
     },
+    /// An order was delivered
     OrderDelivered {
         order_id: Uuid,
         delivered_at: DateTime<Utc>,
     },
+    /// An order was canceled
     OrderCanceled {
         order_id: Uuid,
         reason: String,
     },
+    /// An order was refunded, fully or in part
     OrderRefunded {
         order_id: Uuid,
         refund_id: String,
         amount: Decimal,
         reason: String,
     },
+    /// Stock was reserved for an order
     InventoryReserved {
         order_id: Uuid,
         product_ids: Vec<Uuid>,
     },
+    /// An order's stock reservations were released
     InventoryReleased {
         order_id: Uuid,
         product_ids: Vec<Uuid>,
     },
 }
 
+impl OrderEvent {
+    /// Order the event belongs to
+    pub fn order_id(&self) -> Uuid {
+        match self {
+            OrderEvent::OrderCreated { order_id, .. }
+            | OrderEvent::OrderStatusChanged { order_id, .. }
+            | OrderEvent::PaymentReceived { order_id, .. }
+            | OrderEvent::PaymentFailed { order_id, .. }
+            | OrderEvent::OrderShipped { order_id, .. }
+            | OrderEvent::OrderDelivered { order_id, .. }
+            | OrderEvent::OrderCanceled { order_id, .. }
+            | OrderEvent::OrderRefunded { order_id, .. }
+            | OrderEvent::InventoryReserved { order_id, .. }
+            | OrderEvent::InventoryReleased { order_id, .. } => *order_id,
+        }
+    }
+
+    /// Event name as stored in `order_events.event_type`
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            OrderEvent::OrderCreated { .. } => "order_created",
+            OrderEvent::OrderStatusChanged { .. } => "order_status_changed",
+            OrderEvent::PaymentReceived { .. } => "payment_received",
+            OrderEvent::PaymentFailed { .. } => "payment_failed",
+            OrderEvent::OrderShipped { .. } => "order_shipped",
+            OrderEvent::OrderDelivered { .. } => "order_delivered",
+            OrderEvent::OrderCanceled { .. } => "order_canceled",
+            OrderEvent::OrderRefunded { .. } => "order_refunded",
+            OrderEvent::InventoryReserved { .. } => "inventory_reserved",
+            OrderEvent::InventoryReleased { .. } => "inventory_released",
+        }
+    }
+}
+
 /// Order transition for audit trail
 #[derive(Debug, Clone)]
 pub struct OrderTransition {
@@ -175,18 +224,49 @@ impl OrderTransition {
 /// Order event dispatcher for pub/sub pattern
 #[derive(Default)]
 pub struct OrderEventDispatcher {
-    // In a real implementation, this would have:
-    // - Event bus connection
-    // - Registered event handlers
-    // - Async dispatcher
+    /// Events are appended to the partitioned `order_events` table when set
+    pool: Option<sqlx::PgPool>,
+    /// Payments are published to the event bus when set
+    events: Option<EventBus>,
 }
 
 impl OrderEventDispatcher {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Record dispatched events in `order_events`
+    pub fn with_pool(mut self, pool: sqlx::PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Publish payments to the event bus
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish(&self, event: DomainEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
+    }
+
+    fn publish_payment(&self, order: &Order, payment_id: &str, status: &str, amount: Decimal) {
+        self.publish(DomainEvent::PaymentUpdated(PaymentUpdated {
+            payment_id: Uuid::parse_str(payment_id).ok(),
+            order_id: order.id,
+            status: status.to_string(),
+            previous_status: Some(status_label(&order.payment_status)),
+            amount,
+            currency: order.currency.clone(),
+            gateway: None,
+        }));
+    }
     
-    /// Dispatch order created event
+    /// Dispatch order created event; the order service publishes it through
+    /// the outbox with the order
     pub async fn order_created(&self, order: &Order) -> Result<()> {
         let event = OrderEvent::OrderCreated {
             order_id: order.id,
@@ -194,7 +274,6 @@ impl OrderEventDispatcher {
             total: order.total,
             currency: order.currency.clone(),
         };
-        
         self.dispatch(event).await
     }
     
@@ -212,6 +291,7 @@ impl OrderEventDispatcher {
     
     /// Dispatch payment received event
     pub async fn payment_received(&self, order: &Order, payment_id: String, amount: Decimal) -> Result<()> {
+        self.publish_payment(order, &payment_id, "paid", amount);
         let event = OrderEvent::PaymentReceived {
             order_id: order.id,
             payment_id,
@@ -224,6 +304,7 @@ impl OrderEventDispatcher {
     
     /// Dispatch payment failed event
     pub async fn payment_failed(&self, order: &Order, payment_id: String, error: String) -> Result<()> {
+        self.publish_payment(order, &payment_id, "failed", order.total);
         let event = OrderEvent::PaymentFailed {
             order_id: order.id,
             payment_id,
@@ -312,7 +393,21 @@ impl OrderEventDispatcher {
         // - Call registered handlers
         
         log::info!("Dispatching order event: {:?}", event);
-        
+
+        // The event log is best effort: a failed insert must not fail the order operation
+        if let Some(ref pool) = self.pool {
+            let payload = serde_json::to_value(&event).unwrap_or_default();
+            if let Err(e) = sqlx::query("INSERT INTO order_events (order_id, event_type, payload) VALUES ($1, $2, $3)")
+                .bind(event.order_id())
+                .bind(event.event_type())
+                .bind(payload)
+                .execute(pool)
+                .await
+            {
+                log::warn!("Failed to record {} event for order {}: {}", event.event_type(), event.order_id(), e);
+            }
+        }
+
         Ok(())
     }
 }
@@ -355,4 +450,14 @@ mod tests {
         assert!(!OrderStatus::Pending.is_terminal());
         assert!(!OrderStatus::Confirmed.is_terminal());
     }
+
+    #[test]
+    fn test_event_type_matches_payload_tag() {
+        let order_id = Uuid::new_v4();
+        let event = OrderEvent::OrderCanceled { order_id, reason: "customer request".to_string() };
+
+        let payload = serde_json::to_value(&event).unwrap();
+        assert_eq!(payload["type"], event.event_type());
+        assert_eq!(event.order_id(), order_id);
+    }
 }
\ No newline at end of file
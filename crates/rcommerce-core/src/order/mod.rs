@@ -2,6 +2,8 @@ pub mod service;
 pub mod lifecycle;
 pub mod fulfillment;
 pub mod calculation;
+pub mod shipments;
+pub mod numbering;
 
 use uuid::Uuid;
 use rust_decimal::Decimal;
@@ -12,7 +14,15 @@ use chrono::{DateTime, Utc};
 pub use service::OrderService;
 pub use lifecycle::{OrderStatus, OrderEvent, OrderTransition};
 pub use fulfillment::{Fulfillment, FulfillmentStatus, TrackingInfo};
-pub use calculation::{OrderCalculator, OrderTotals};
+pub use calculation::{CurrencyConversion, OrderCalculator, OrderTotals};
+pub use numbering::{OrderNumberConfig, OrderNumberFormat, OrderNumbers};
+pub use shipments::{
+    plan_fulfillment, Backorder, BackorderJob, BackorderReport, FulfillmentPlan, LocationStock, OpenOrderLine,
+    BatchLabelRequest, LabelFile, LabelFormat, OrderShipments, PickListLabel, PlannedLine, PlannedShipment,
+    SetShippingPreferenceRequest, Shipment, ShipmentLabelRequest,
+    ShipmentService, ShipmentCustoms, CustomsLine,
+    ShippingOrder, ShippingPreference,
+};
 
 /// Core order struct
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -94,9 +104,19 @@ pub struct CreateOrderRequest {
     pub items: Vec<CreateOrderItem>,
     pub currency: String,
     pub subtotal: Decimal,
+    /// Price of add-ons (gift wrap, ...), added to the order subtotal
+    pub addon_total: Decimal,
+    /// Tax on add-ons, included in `tax_total`
+    pub addon_tax: Decimal,
     pub tax_total: Decimal,
     pub shipping_total: Decimal,
     pub discount_total: Decimal,
+    /// Who pays duties and import taxes of a cross-border order
+    pub incoterm: Option<crate::tax::Incoterm>,
+    /// Duties and DDP fees prepaid at checkout, added to the total
+    pub duty_total: Decimal,
+    /// Import VAT/GST prepaid at checkout, added to the total
+    pub import_tax_total: Decimal,
     pub total: Decimal,
     pub notes: Option<String>,
     pub tags: Option<Vec<String>>,
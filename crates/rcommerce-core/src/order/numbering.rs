@@ -0,0 +1,225 @@
+//! Order numbers
+//!
+//! Orders keep their UUID and also get a human-friendly number from a
+//! sequence, e.g. `INV-2026-000123`. Each channel listed under
+//! `[order_numbers.channels]` has its own format and sequence; every other
+//! channel uses the default one. With `yearly_reset` the year is part of the
+//! number and the sequence starts again each (UTC) year.
+//!
+//! Numbers are taken on the order's transaction: an order that rolls back
+//! gives its number back, so there are no gaps, and orders of one sequence
+//! get their numbers one at a time.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::{Error, Result};
+
+/// Sequence of channels without their own format
+const DEFAULT_SCOPE: &str = "default";
+
+/// How the numbers of a sequence look
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderNumberFormat {
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Between the prefix, the year and the sequence number
+    #[serde(default = "default_separator")]
+    pub separator: String,
+
+    /// Sequence numbers are zero-padded to this many digits
+    #[serde(default = "default_padding")]
+    pub padding: usize,
+
+    /// Put the year in the number and start again at `start` each year
+    #[serde(default)]
+    pub yearly_reset: bool,
+
+    /// First number of the sequence; raising it skips ahead, e.g. to carry
+    /// on from numbers issued by another system
+    #[serde(default = "default_start")]
+    pub start: i64,
+}
+
+impl Default for OrderNumberFormat {
+    fn default() -> Self {
+        Self {
+            prefix: default_prefix(),
+            separator: default_separator(),
+            padding: default_padding(),
+            yearly_reset: false,
+            start: default_start(),
+        }
+    }
+}
+
+impl OrderNumberFormat {
+    /// The order number of a sequence number, in the given year
+    pub fn format(&self, value: i64, year: i32) -> String {
+        let mut number = String::new();
+        if !self.prefix.is_empty() {
+            number.push_str(&self.prefix);
+            number.push_str(&self.separator);
+        }
+        if self.yearly_reset {
+            number.push_str(&year.to_string());
+            number.push_str(&self.separator);
+        }
+        number.push_str(&format!("{:0width$}", value, width = self.padding));
+        number
+    }
+
+    fn validate(&self, name: &str) -> Result<()> {
+        if self.prefix.len() > 20 || self.separator.len() > 3 {
+            return Err(Error::Config(format!("{}: prefix is limited to 20 characters and separator to 3", name)));
+        }
+        if self.padding > 12 {
+            return Err(Error::Config(format!("{}.padding must be at most 12", name)));
+        }
+        if self.start < 1 {
+            return Err(Error::Config(format!("{}.start must be positive", name)));
+        }
+        Ok(())
+    }
+}
+
+/// Order number configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderNumberConfig {
+    /// Format of channels without their own
+    #[serde(flatten)]
+    pub default: OrderNumberFormat,
+
+    /// Channels (`web`, `amazon`, `ebay`, ...) numbered in their own sequence
+    #[serde(default)]
+    pub channels: HashMap<String, OrderNumberFormat>,
+}
+
+impl OrderNumberConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.default.validate("order_numbers")?;
+        for (channel, format) in &self.channels {
+            if channel.is_empty() || channel.len() > 20 || channel == DEFAULT_SCOPE {
+                return Err(Error::Config(format!("order_numbers.channels: invalid channel name '{}'", channel)));
+            }
+            format.validate(&format!("order_numbers.channels.{}", channel))?;
+        }
+        Ok(())
+    }
+}
+
+fn default_prefix() -> String {
+    "ORD".to_string()
+}
+
+fn default_separator() -> String {
+    "-".to_string()
+}
+
+fn default_padding() -> usize {
+    6
+}
+
+fn default_start() -> i64 {
+    1
+}
+
+/// Allocates order numbers
+#[derive(Debug, Clone, Default)]
+pub struct OrderNumbers {
+    config: OrderNumberConfig,
+}
+
+impl OrderNumbers {
+    pub fn new(config: OrderNumberConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &OrderNumberConfig {
+        &self.config
+    }
+
+    /// The sequence a channel's orders are numbered in, and its format
+    pub fn sequence(&self, channel: &str) -> (&str, &OrderNumberFormat) {
+        match self.config.channels.get_key_value(channel) {
+            Some((channel, format)) => (channel.as_str(), format),
+            None => (DEFAULT_SCOPE, &self.config.default),
+        }
+    }
+
+    /// Take the next number for an order of the channel placed at `at`, on
+    /// the order's transaction; the sequence stays locked until it ends
+    pub async fn next(&self, conn: &mut PgConnection, channel: &str, at: DateTime<Utc>) -> Result<String> {
+        let (scope, format) = self.sequence(channel);
+        let period = if format.yearly_reset { at.year() } else { 0 };
+        let value = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO order_number_sequences (scope, period, last_value)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (scope, period) DO UPDATE
+            SET last_value = GREATEST(order_number_sequences.last_value + 1, $3), updated_at = NOW()
+            RETURNING last_value
+            "#
+        )
+        .bind(scope)
+        .bind(period)
+        .bind(format.start)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to allocate order number: {}", e)))?;
+        Ok(format.format(value, at.year()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let format = OrderNumberFormat::default();
+        assert_eq!(format.format(42, 2026), "ORD-000042");
+
+        let invoice = OrderNumberFormat { prefix: "INV".to_string(), yearly_reset: true, ..Default::default() };
+        assert_eq!(invoice.format(123, 2026), "INV-2026-000123");
+
+        let bare = OrderNumberFormat { prefix: String::new(), padding: 0, ..Default::default() };
+        assert_eq!(bare.format(1234567, 2026), "1234567");
+    }
+
+    #[test]
+    fn test_channel_sequences() {
+        let config: OrderNumberConfig = toml::from_str(
+            r#"
+            prefix = "INV"
+            yearly_reset = true
+
+            [channels.amazon]
+            prefix = "AMZ"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        let numbers = OrderNumbers::new(config);
+        assert_eq!(numbers.sequence("web").0, "default");
+        assert_eq!(numbers.sequence("web").1.format(7, 2026), "INV-2026-000007");
+        assert_eq!(numbers.sequence("amazon").0, "amazon");
+        assert_eq!(numbers.sequence("amazon").1.format(7, 2026), "AMZ-000007");
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = OrderNumberConfig {
+            default: OrderNumberFormat { start: 0, ..Default::default() },
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        let mut config = OrderNumberConfig::default();
+        config.channels.insert("default".to_string(), OrderNumberFormat::default());
+        assert!(config.validate().is_err());
+    }
+}
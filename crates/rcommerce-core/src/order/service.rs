@@ -10,9 +10,10 @@ use rust_decimal::Decimal;
 use tracing::{debug, info, warn};
 
 use crate::{Result, Error};
-use crate::order::{Order, OrderItem, CreateOrderRequest, CreateOrderItem, OrderStatus, PaymentStatus};
+use crate::order::{Order, OrderItem, CreateOrderRequest, CreateOrderItem, OrderNumbers, OrderStatus, PaymentStatus};
 use crate::order::lifecycle::OrderEventDispatcher;
-use crate::repository::Database;
+use crate::events::{status_label, DomainEvent, OrderCreated};
+use crate::repository::{enqueue_event, Database};
 use crate::payment::PaymentGateway;
 use crate::inventory::InventoryService;
 use crate::tax::{
@@ -20,6 +21,7 @@ use crate::tax::{
     TransactionType, VatId, TaxCalculation,
 };
 use crate::models::Address;
+use crate::services::bundle_service::stock_lines;
 
 /// Order service with integrated tax calculation
 pub struct OrderService {
@@ -28,6 +30,7 @@ pub struct OrderService {
     inventory_service: InventoryService,
     event_dispatcher: OrderEventDispatcher,
     tax_service: Option<Arc<dyn TaxService>>,
+    order_numbers: OrderNumbers,
 }
 
 impl OrderService {
@@ -44,6 +47,7 @@ impl OrderService {
             inventory_service,
             event_dispatcher,
             tax_service: None,
+            order_numbers: OrderNumbers::default(),
         }
     }
 
@@ -53,6 +57,12 @@ impl OrderService {
         self
     }
     
+    /// Number orders with these sequences instead of the default one
+    pub fn with_order_numbers(mut self, order_numbers: OrderNumbers) -> Self {
+        self.order_numbers = order_numbers;
+        self
+    }
+    
     /// Create a new order with tax calculation
     pub async fn create_order(&self, request: CreateOrderRequest) -> Result<Order> {
         info!("Creating order for customer {:?}", request.customer_id);
@@ -98,10 +108,10 @@ impl OrderService {
 
         // Use calculated tax or fall back to provided values
         let tax_total = tax_calculation.as_ref()
-            .map(|c| c.total_tax)
+            .map(|c| c.total_tax + request.addon_tax)
             .unwrap_or(request.tax_total);
         
-        // Validate and reserve inventory for items
+        // Validate items; their stock is reserved with the order
         let mut order_items = Vec::new();
         let mut subtotal = Decimal::ZERO;
         let mut total_item_tax = Decimal::ZERO;
@@ -110,18 +120,6 @@ impl OrderService {
             // Validate product exists and is active
             self.validate_product(item.product_id).await?;
             
-            // Reserve inventory
-            let reservation = self.inventory_service.reserve_stock(
-                crate::inventory::StockReservation::new(
-                    item.product_id,
-                    item.variant_id,
-                    self.get_default_location_id().await?, // TODO: Support multiple locations
-                    Uuid::nil(), // Will be updated after order creation
-                    item.quantity,
-                    chrono::Utc::now() + chrono::Duration::minutes(30),
-                )
-            ).await?;
-            
             // Calculate item totals
             let item_subtotal = item.price * Decimal::from(item.quantity);
             
@@ -152,27 +150,29 @@ impl OrderService {
                 name: format!("Item {}", index + 1), // TODO: Fetch from product
                 variant_name: None,
                 weight: None, // TODO: Fetch from product
-                metadata: serde_json::json!({
-                    "reservation_id": reservation.id,
-                }),
+                metadata: serde_json::json!({}),
                 created_at: chrono::Utc::now(),
             };
             
             order_items.push(order_item);
         }
         
+        // Add-ons are charged with the items
+        subtotal += request.addon_total;
+        
         // Calculate shipping tax
         let _shipping_tax = tax_calculation.as_ref()
             .map(|c| c.shipping_tax)
             .unwrap_or_default();
         
-        // Total includes: subtotal + item tax + shipping + shipping tax - discount
-        let total = subtotal + tax_total + request.shipping_total - request.discount_total;
+        // Total includes: subtotal + item tax + shipping + shipping tax - discount,
+        // plus duties and import taxes prepaid with DDP
+        let total = subtotal + tax_total + request.shipping_total - request.discount_total
+            + request.duty_total + request.import_tax_total;
         
-        // Generate order number
-        let order_number = self.generate_order_number().await?;
-        
-        // Create order
+        // Create the order, its items and its order.created event together
+        let mut tx = self.db.pool().begin().await?;
+        let order_number = self.order_numbers.next(&mut tx, "web", chrono::Utc::now()).await?;
         let order_id = Uuid::new_v4();
         let order = sqlx::query_as::<_, Order>(
             r#"
@@ -181,14 +181,14 @@ impl OrderService {
                 billing_address_id, shipping_address_id,
                 status, fulfillment_status, payment_status,
                 currency, subtotal, tax_total, shipping_total, discount_total, total,
-                notes, tags, metadata
+                notes, tags, metadata, incoterm, duty_total, import_tax_total
             )
             VALUES (
                 $1, $2, $3, $4,
                 $5, $6,
                 'pending', 'pending', 'pending',
                 $7, $8, $9, $10, $11, $12,
-                $13, $14, $15
+                $13, $14, $15, $16, $17, $18
             )
             RETURNING *
             "#
@@ -208,22 +208,16 @@ impl OrderService {
         .bind(request.notes)
         .bind(request.tags)
         .bind(request.metadata)
-        .fetch_one(self.db.pool())
+        .bind(request.incoterm)
+        .bind(request.duty_total)
+        .bind(request.import_tax_total)
+        .fetch_one(&mut *tx)
         .await?;
         
         // Create order items
         for mut item in order_items {
             item.order_id = order_id;
             
-            // Check for reservation before moving metadata
-            let reservation_id: Option<Uuid> = if let serde_json::Value::Object(ref map) = &item.metadata {
-                map.get("reservation_id")
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| Uuid::parse_str(s).ok())
-            } else {
-                None
-            };
-            
             sqlx::query(
                 r#"
                 INSERT INTO order_items (
@@ -248,19 +242,43 @@ impl OrderService {
             .bind(item.variant_name)
             .bind(item.weight)
             .bind(&item.metadata)
-            .execute(self.db.pool())
+            .execute(&mut *tx)
             .await?;
-            
-            // Update reservation reference if found
-            if let Some(reservation_id) = reservation_id {
-                sqlx::query("UPDATE stock_reservations SET order_id = $1 WHERE id = $2")
-                    .bind(order_id)
-                    .bind(reservation_id)
-                    .execute(self.db.pool())
-                    .await?;
-            }
         }
         
+        // Reserve the stock, bundles by their components; an item out of
+        // stock rolls the whole order back
+        let items: Vec<_> = request.items.iter().map(|item| (item.product_id, item.variant_id, item.quantity)).collect();
+        let product_ids: Vec<Uuid> = items.iter().map(|(product_id, _, _)| *product_id).collect();
+        let components = sqlx::query_as::<_, (Uuid, Uuid, i32)>(
+            r#"
+            SELECT bc.bundle_product_id, bc.component_product_id, bc.quantity
+            FROM bundle_components bc
+            JOIN products b ON b.id = bc.bundle_product_id AND b.product_type = 'bundle'
+            WHERE bc.bundle_product_id = ANY($1) AND NOT bc.is_optional
+            "#
+        )
+        .bind(&product_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (product_id, variant_id, quantity) in stock_lines(&items, &components) {
+            self.inventory_service
+                .reserve_for_order(&mut tx, order_id, product_id, variant_id, quantity)
+                .await?;
+        }
+        
+        enqueue_event(&mut tx, &DomainEvent::OrderCreated(OrderCreated {
+            order_id: order.id,
+            order_number: order.order_number.clone(),
+            customer_id: order.customer_id,
+            email: order.customer_email.clone(),
+            total: order.total,
+            currency: order.currency.clone(),
+            status: status_label(&order.status),
+        }))
+        .await?;
+        tx.commit().await?;
+        
         // Record tax transaction for reporting if tax was calculated
         if let (Some(tax_service), Some(calculation)) = (&self.tax_service, tax_calculation) {
             if let Err(e) = tax_service.record_tax_transaction(order_id, &calculation).await {
@@ -269,7 +287,7 @@ impl OrderService {
             }
         }
         
-        // Dispatch order created event
+        // Record the order created event
         self.event_dispatcher.order_created(&order).await?;
         
         info!("Order created: id={}, number={}, total={}", order_id, order.order_number, total);
@@ -491,9 +509,8 @@ impl OrderService {
             self.update_order_status(order_id, OrderStatus::Confirmed).await?;
         }
         
-        // Commit inventory reservations
-        self.commit_inventory_reservations(order_id).await?;
-        
+        // Marking the order paid turned its reservations into stock
+        // decrements (see migration 065)
         Ok(confirmed_payment)
     }
     
@@ -508,8 +525,8 @@ impl OrderService {
             return Err(Error::validation("Order cannot be canceled in current status"));
         }
         
-        // Release inventory reservations
-        self.release_inventory_reservations(order_id).await?;
+        // Cancelling the order releases its reservations and puts unshipped
+        // committed stock back (see migration 065)
         
         // Update cancellation reason in metadata first (before reason is consumed)
         sqlx::query("UPDATE orders SET metadata = jsonb_set(metadata, '{cancellation_reason}', $1) WHERE id = $2")
@@ -638,52 +655,6 @@ impl OrderService {
         matches!(order.status, Pending | Confirmed | Processing)
     }
     
-    async fn generate_order_number(&self) -> Result<String> {
-        // Simple incrementing order number
-        // In production, use a more sophisticated system
-        let prefix = "ORD";
-        let timestamp = chrono::Utc::now().format("%Y%m%d");
-        let random: u32 = rand::random();
-        
-        Ok(format!("{}-{}-{}", prefix, timestamp, random))
-    }
-    
-    async fn get_default_location_id(&self) -> Result<Uuid> {
-        // For now, return a static location ID
-        // In production, select based on routing rules
-        Ok(Uuid::nil())
-    }
-    
-    async fn commit_inventory_reservations(&self, order_id: Uuid) -> Result<()> {
-        let reservations = sqlx::query_as::<_, crate::inventory::StockReservation>(
-            "SELECT * FROM stock_reservations WHERE order_id = $1 AND status = 'active'"
-        )
-        .bind(order_id)
-        .fetch_all(self.db.pool())
-        .await?;
-        
-        for reservation in reservations {
-            self.inventory_service.commit_reservation(reservation.id).await?;
-        }
-        
-        Ok(())
-    }
-    
-    async fn release_inventory_reservations(&self, order_id: Uuid) -> Result<()> {
-        let reservations = sqlx::query_as::<_, crate::inventory::StockReservation>(
-            "SELECT * FROM stock_reservations WHERE order_id = $1 AND status IN ('active', 'committed')"
-        )
-        .bind(order_id)
-        .fetch_all(self.db.pool())
-        .await?;
-        
-        for reservation in reservations {
-            self.inventory_service.release_reservation(reservation.id).await?;
-        }
-        
-        Ok(())
-    }
-    
     async fn process_refund(&self, _order_id: Uuid, _reason: String) -> Result<()> {
         // TODO: Implement refund processing via payment gateway
         log::info!("Processing refund for order {}", _order_id);
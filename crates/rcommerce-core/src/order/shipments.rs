@@ -0,0 +1,1160 @@
+//! Shipment planning and backorders
+//!
+//! Fulfilling an order plans its shipments across inventory locations:
+//! the location that can send the most lines complete is picked first,
+//! then the next for what is left, so orders split across as few parcels
+//! as possible. With `ship_partial` whatever no location has in stock is
+//! backordered and ships later; with `ship_complete` nothing ships until
+//! everything can. The order's preference wins over its customer's, which
+//! wins over `fulfillment.default_shipping_preference`.
+//!
+//! Shipped units leave stock as `out` stock movements: first the units the
+//! order's payment committed at the location (`reserved_quantity`), then
+//! `available_quantity`.
+//! Backordered units wait in `order_backorders` until the `backorders`
+//! job (`[fulfillment]`), or a purchase order being received, finds them
+//! in stock and ships them. Customers are told when their order is split
+//! and when backordered items ship.
+//!
+//! Labels for parcels crossing a border carry a customs declaration of
+//! their items (HS codes and origins from `product_customs`) with the
+//! incoterm chosen at checkout.
+//!
+//! Bought labels are kept in media storage under
+//! `labels/<shipment id>/<token>.<pdf|zpl|png>`, whatever the carrier sent
+//! (inline or as a link), so they can be reprinted and printed in batches
+//! for a pick list of orders.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::FulfillmentConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::media::MediaStorage;
+use crate::models::FulfillmentStatus;
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, ShipmentRepository};
+use crate::reports::render::zip_stored;
+use crate::shipping::{
+    ContentsType, CustomsInfo, CustomsItem, NonDeliveryOption, Package, Shipment as CarrierShipment, ShippingProviderFactory,
+};
+use crate::tax::Incoterm;
+use crate::{Error, Result};
+
+/// Orders with backorders re-planned per run of the `backorders` job
+const RELEASE_BATCH: i64 = 200;
+
+/// Most orders whose labels are printed at once
+const MAX_BATCH_LABEL_ORDERS: usize = 200;
+
+/// How long to wait for a carrier's label link
+const LABEL_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether an order waits to ship in one go or ships what is in stock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "shipping_preference", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ShippingPreference {
+    /// Hold the order until every item is in stock
+    ShipComplete,
+    /// Ship what is in stock now and backorder the rest
+    #[default]
+    ShipPartial,
+}
+
+/// An order with what decides how it ships
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ShippingOrder {
+    pub id: Uuid,
+    pub order_number: String,
+    pub email: String,
+    pub customer_id: Option<Uuid>,
+    /// Order status, as text
+    pub status: String,
+    pub order_preference: Option<ShippingPreference>,
+    pub customer_preference: Option<ShippingPreference>,
+    /// Customer's email opt-in (guests always get order emails)
+    pub email_notifications: bool,
+    /// Location the customer collects the order from; None if it ships
+    pub pickup_location_id: Option<Uuid>,
+}
+
+impl ShippingOrder {
+    /// The order's preference, else its customer's, else `default`
+    pub fn preference(&self, default: ShippingPreference) -> ShippingPreference {
+        self.order_preference.or(self.customer_preference).unwrap_or(default)
+    }
+
+    /// Whether the order can still ship
+    pub fn can_ship(&self) -> bool {
+        matches!(self.status.as_str(), "confirmed" | "processing")
+    }
+}
+
+/// An order line with units not yet in a shipment
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OpenOrderLine {
+    pub order_item_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub title: String,
+    /// Units still to ship
+    pub quantity: i32,
+}
+
+/// Units of a product available at a location
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LocationStock {
+    pub location_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub available: i32,
+}
+
+/// Units of an order line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PlannedLine {
+    pub order_item_id: Uuid,
+    pub quantity: i32,
+}
+
+/// A parcel from one location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedShipment {
+    pub location_id: Uuid,
+    pub lines: Vec<PlannedLine>,
+}
+
+/// How an order's open lines ship
+#[derive(Debug, Clone, Serialize)]
+pub struct FulfillmentPlan {
+    pub preference: ShippingPreference,
+    pub shipments: Vec<PlannedShipment>,
+    /// Units waiting for stock
+    pub backordered: Vec<PlannedLine>,
+}
+
+impl FulfillmentPlan {
+    /// Whether the order arrives in more than one parcel
+    pub fn is_split(&self) -> bool {
+        self.shipments.len() > 1 || (!self.shipments.is_empty() && !self.backordered.is_empty())
+    }
+}
+
+/// A shipment (fulfillment) of an order
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Shipment {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub location_id: Option<Uuid>,
+    pub status: FulfillmentStatus,
+    /// Ships units that were backordered
+    pub is_backorder: bool,
+    /// Carrier the label was bought from
+    pub tracking_company: Option<String>,
+    pub tracking_number: Option<String>,
+    pub service_code: Option<String>,
+    pub label_url: Option<String>,
+    /// Media storage key of the stored label
+    #[serde(skip)]
+    pub label_key: Option<String>,
+    pub label_format: Option<LabelFormat>,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub items: Vec<PlannedLine>,
+}
+
+/// File format of a shipping label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "label_format", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LabelFormat {
+    Pdf,
+    /// Zebra printer language, for thermal printers
+    Zpl,
+    Png,
+}
+
+impl LabelFormat {
+    /// The format of a label file by its first bytes
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let start = data.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(data.len());
+        let data = &data[start..];
+        if data.starts_with(b"%PDF") {
+            Some(LabelFormat::Pdf)
+        } else if data.starts_with(b"^XA") {
+            Some(LabelFormat::Zpl)
+        } else if data.starts_with(b"\x89PNG") {
+            Some(LabelFormat::Png)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LabelFormat::Pdf => "pdf",
+            LabelFormat::Zpl => "zpl",
+            LabelFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            LabelFormat::Pdf => "application/pdf",
+            LabelFormat::Zpl => "application/zpl",
+            LabelFormat::Png => "image/png",
+        }
+    }
+}
+
+/// A label file to download
+#[derive(Debug, Clone)]
+pub struct LabelFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// A shipment of an order on a pick list, with its stored label if any
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PickListLabel {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub shipment_id: Option<Uuid>,
+    pub label_key: Option<String>,
+    pub label_format: Option<LabelFormat>,
+}
+
+/// Print the labels of a pick list of orders
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchLabelRequest {
+    pub order_ids: Vec<Uuid>,
+}
+
+/// Units of an order line waiting for stock
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Backorder {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub order_item_id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a shipment declares to customs
+#[derive(Debug, Clone, Default)]
+pub struct ShipmentCustoms {
+    /// The order's incoterm; None for orders placed as domestic
+    pub incoterm: Option<Incoterm>,
+    pub currency: String,
+    pub lines: Vec<CustomsLine>,
+}
+
+/// Units of an order line in a shipment, as declared to customs
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CustomsLine {
+    pub description: String,
+    pub quantity: i32,
+    /// Price of the units
+    pub value: Decimal,
+    pub weight: Option<Decimal>,
+    pub weight_unit: Option<String>,
+    pub hs_code: Option<String>,
+    pub origin_country: Option<String>,
+}
+
+impl ShipmentCustoms {
+    /// The customs declaration of a parcel from `origin_country`; items
+    /// without a country of origin are declared as made there
+    pub fn to_customs_info(&self, origin_country: &str) -> CustomsInfo {
+        let customs_items: Vec<CustomsItem> = self
+            .lines
+            .iter()
+            .map(|line| CustomsItem {
+                description: line.description.clone(),
+                quantity: line.quantity,
+                value: line.value,
+                currency: self.currency.clone(),
+                weight: line.weight,
+                weight_unit: line.weight_unit.clone(),
+                hs_tariff_number: line.hs_code.clone(),
+                origin_country: line.origin_country.clone().unwrap_or_else(|| origin_country.to_uppercase()),
+            })
+            .collect();
+        let mut descriptions: Vec<&str> = Vec::new();
+        for line in &self.lines {
+            if !descriptions.contains(&line.description.as_str()) {
+                descriptions.push(&line.description);
+            }
+        }
+
+        CustomsInfo {
+            contents_type: ContentsType::Merchandise,
+            contents_description: descriptions.join(", "),
+            non_delivery_option: NonDeliveryOption::Return,
+            restriction_type: None,
+            restriction_comments: None,
+            declaration_value: customs_items.iter().map(|item| item.value).sum(),
+            declaration_currency: self.currency.clone(),
+            customs_items,
+            incoterm: self.incoterm,
+        }
+    }
+}
+
+/// An order's shipments and what is still backordered
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderShipments {
+    pub preference: ShippingPreference,
+    pub shipments: Vec<Shipment>,
+    pub backorders: Vec<Backorder>,
+}
+
+/// Set (or with null, clear) a shipping preference
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetShippingPreferenceRequest {
+    pub shipping_preference: Option<ShippingPreference>,
+}
+
+/// Buy a shipping label for a shipment; the carrier and service default
+/// to `shipping.default_provider` and its first domestic (or, for parcels
+/// going abroad, international) service
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShipmentLabelRequest {
+    pub provider_id: Option<String>,
+    pub service_code: Option<String>,
+    pub weight: Decimal,
+    #[serde(default = "default_label_weight_unit")]
+    pub weight_unit: String,
+}
+
+fn default_label_weight_unit() -> String {
+    "lb".to_string()
+}
+
+/// What a run of the `backorders` job did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackorderReport {
+    /// Orders with backorders that were checked
+    pub orders_checked: usize,
+    pub shipments_created: usize,
+    /// Orders with nothing left backordered
+    pub orders_completed: usize,
+    pub failed: usize,
+}
+
+impl std::fmt::Display for BackorderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} backordered orders checked, {} shipments created, {} orders complete, {} failed",
+            self.orders_checked, self.shipments_created, self.orders_completed, self.failed
+        )
+    }
+}
+
+type StockKey = (Uuid, Uuid, Option<Uuid>);
+
+/// Plan shipments for `lines` from `stock`. Locations are picked one at a
+/// time, each the one sending the most lines complete (then the most
+/// units), taking what it has of every line; ties go to the location
+/// listed first in `stock`.
+pub fn plan_fulfillment(
+    lines: &[OpenOrderLine],
+    stock: &[LocationStock],
+    preference: ShippingPreference,
+) -> FulfillmentPlan {
+    let mut locations: Vec<Uuid> = Vec::new();
+    let mut available: HashMap<StockKey, i32> = HashMap::new();
+    for level in stock {
+        if !locations.contains(&level.location_id) {
+            locations.push(level.location_id);
+        }
+        *available
+            .entry((level.location_id, level.product_id, level.variant_id))
+            .or_default() += level.available.max(0);
+    }
+    let mut remaining: Vec<i32> = lines.iter().map(|line| line.quantity.max(0)).collect();
+
+    let mut shipments = Vec::new();
+    loop {
+        let mut best: Option<(Uuid, usize, i32)> = None;
+        for &location_id in &locations {
+            let (complete, units) = coverage(location_id, lines, &remaining, &available);
+            if units > 0 && best.map_or(true, |(_, c, u)| (complete, units) > (c, u)) {
+                best = Some((location_id, complete, units));
+            }
+        }
+        let Some((location_id, _, _)) = best else {
+            break;
+        };
+
+        let mut shipment = PlannedShipment {
+            location_id,
+            lines: Vec::new(),
+        };
+        for (line, left) in lines.iter().zip(remaining.iter_mut()) {
+            let in_stock = available
+                .entry((location_id, line.product_id, line.variant_id))
+                .or_default();
+            let take = (*left).min(*in_stock);
+            if take > 0 {
+                *left -= take;
+                *in_stock -= take;
+                add_line(&mut shipment.lines, line.order_item_id, take);
+            }
+        }
+        shipments.push(shipment);
+    }
+
+    let backordered: Vec<PlannedLine> = lines
+        .iter()
+        .zip(&remaining)
+        .filter(|(_, left)| **left > 0)
+        .map(|(line, left)| PlannedLine {
+            order_item_id: line.order_item_id,
+            quantity: *left,
+        })
+        .collect();
+
+    if preference == ShippingPreference::ShipComplete && !backordered.is_empty() {
+        // Hold everything until it can all ship
+        let held = lines
+            .iter()
+            .filter(|line| line.quantity > 0)
+            .map(|line| PlannedLine {
+                order_item_id: line.order_item_id,
+                quantity: line.quantity,
+            })
+            .collect();
+        return FulfillmentPlan {
+            preference,
+            shipments: Vec::new(),
+            backordered: held,
+        };
+    }
+
+    FulfillmentPlan {
+        preference,
+        shipments,
+        backordered,
+    }
+}
+
+/// Lines a location can send complete, and units it can send
+fn coverage(
+    location_id: Uuid,
+    lines: &[OpenOrderLine],
+    remaining: &[i32],
+    available: &HashMap<StockKey, i32>,
+) -> (usize, i32) {
+    // Lines of the same product share its stock
+    let mut used: HashMap<StockKey, i32> = HashMap::new();
+    let mut complete = 0;
+    let mut units = 0;
+    for (line, &left) in lines.iter().zip(remaining) {
+        if left == 0 {
+            continue;
+        }
+        let key = (location_id, line.product_id, line.variant_id);
+        let taken = used.entry(key).or_default();
+        let in_stock = available.get(&key).copied().unwrap_or(0) - *taken;
+        let take = left.min(in_stock.max(0));
+        *taken += take;
+        units += take;
+        if take == left {
+            complete += 1;
+        }
+    }
+    (complete, units)
+}
+
+fn add_line(lines: &mut Vec<PlannedLine>, order_item_id: Uuid, quantity: i32) {
+    match lines.iter_mut().find(|line| line.order_item_id == order_item_id) {
+        Some(line) => line.quantity += quantity,
+        None => lines.push(PlannedLine { order_item_id, quantity }),
+    }
+}
+
+/// Ships orders by their preference and ships backorders when restocked
+pub struct ShipmentService<R: ShipmentRepository> {
+    repository: R,
+    config: FulfillmentConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+    shipping: Option<Arc<ShippingProviderFactory>>,
+    default_provider: Option<String>,
+    label_storage: Option<Arc<dyn MediaStorage>>,
+}
+
+impl<R: ShipmentRepository> ShipmentService<R> {
+    pub fn new(repository: R, config: FulfillmentConfig) -> Self {
+        Self {
+            repository,
+            config,
+            notifications: None,
+            shipping: None,
+            default_provider: None,
+            label_storage: None,
+        }
+    }
+
+    /// Buy shipping labels from these carriers, on the account of the
+    /// location each shipment leaves from
+    pub fn with_labels(mut self, shipping: Arc<ShippingProviderFactory>, default_provider: Option<String>) -> Self {
+        self.shipping = Some(shipping);
+        self.default_provider = default_provider;
+        self
+    }
+
+    /// Keep bought labels in this storage, for reprinting and batch printing
+    pub fn with_label_storage(mut self, storage: Arc<dyn MediaStorage>) -> Self {
+        self.label_storage = Some(storage);
+        self
+    }
+
+    /// Queue customer notifications about split orders and backorders
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &FulfillmentConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    async fn get_order(&self, order_id: Uuid) -> Result<ShippingOrder> {
+        self.repository
+            .find_order(order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))
+    }
+
+    async fn plan(&self, order: &ShippingOrder) -> Result<(FulfillmentPlan, Vec<OpenOrderLine>)> {
+        let lines = self.repository.open_lines(order.id).await?;
+        let mut products: Vec<Uuid> = lines.iter().map(|line| line.product_id).collect();
+        products.sort();
+        products.dedup();
+        let mut stock = self.repository.stock(order.id, &products).await?;
+        // Pickup orders are packed where they are collected
+        if let Some(location_id) = order.pickup_location_id {
+            stock.retain(|stock| stock.location_id == location_id);
+        }
+        let plan = plan_fulfillment(&lines, &stock, order.preference(self.config.default_shipping_preference));
+        Ok((plan, lines))
+    }
+
+    /// How the order would ship now, without shipping it
+    pub async fn plan_order(&self, order_id: Uuid) -> Result<FulfillmentPlan> {
+        let order = self.get_order(order_id).await?;
+        Ok(self.plan(&order).await?.0)
+    }
+
+    /// The order's shipments and backorders
+    pub async fn order_shipments(&self, order_id: Uuid) -> Result<OrderShipments> {
+        let order = self.get_order(order_id).await?;
+        Ok(OrderShipments {
+            preference: order.preference(self.config.default_shipping_preference),
+            shipments: self.repository.shipments(order_id).await?,
+            backorders: self.repository.backorders(order_id).await?,
+        })
+    }
+
+    /// Create shipments for what is in stock of the order's open lines
+    /// (all or nothing with `ship_complete`) and backorder the rest
+    pub async fn fulfill_order(&self, order_id: Uuid) -> Result<OrderShipments> {
+        let order = self.get_order(order_id).await?;
+        if !order.can_ship() {
+            return Err(Error::validation(format!(
+                "Orders that are {} cannot be fulfilled",
+                order.status
+            )));
+        }
+        let (plan, lines) = self.plan(&order).await?;
+        if plan.shipments.is_empty() && plan.backordered.is_empty() {
+            return Err(Error::validation("Nothing left to ship on this order"));
+        }
+
+        let was_backordered = !self.repository.backorders(order_id).await?.is_empty();
+        let created = self.repository.apply_plan(&order, &plan, was_backordered).await?;
+        if was_backordered {
+            self.notify_backorder_shipped(&order, &plan, &lines).await;
+        } else if plan.is_split() {
+            self.notify_split(&order, &plan, &lines).await;
+        }
+
+        Ok(OrderShipments {
+            preference: plan.preference,
+            shipments: created,
+            backorders: self.repository.backorders(order_id).await?,
+        })
+    }
+
+    /// Buy a label for a shipment from its location's carrier account,
+    /// shipping from the location's origin address
+    pub async fn create_label(&self, shipment_id: Uuid, request: ShipmentLabelRequest) -> Result<Shipment> {
+        let shipping = self
+            .shipping
+            .as_ref()
+            .ok_or_else(|| Error::shipping("Shipping labels are not configured"))?;
+        if request.weight <= Decimal::ZERO {
+            return Err(Error::validation("weight must be positive"));
+        }
+        let shipment = self
+            .repository
+            .find_shipment(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))?;
+        if !matches!(shipment.status, FulfillmentStatus::Pending | FulfillmentStatus::Processing) {
+            return Err(Error::validation("Labels can only be bought for shipments that have not shipped"));
+        }
+        if self.get_order(shipment.order_id).await?.pickup_location_id.is_some() {
+            return Err(Error::validation("Pickup orders are collected in store and need no label"));
+        }
+
+        let location = match shipment.location_id {
+            Some(location_id) => self.repository.location_code(location_id).await?,
+            None => None,
+        };
+        let provider_id = request
+            .provider_id
+            .or_else(|| self.default_provider.clone())
+            .ok_or_else(|| Error::shipping("No carrier configured for shipping labels"))?;
+        let provider = shipping.get_for_location(location.as_deref(), &provider_id)?;
+
+        let from = shipping
+            .origin(location.as_deref())
+            .ok_or_else(|| match &location {
+                Some(code) => Error::config(format!("No [shipping] origin for location {}", code)),
+                None => Error::config("[shipping] origin is required for shipping labels"),
+            })?
+            .to_address();
+        let to = self
+            .repository
+            .ship_to(shipment.order_id)
+            .await?
+            .ok_or_else(|| Error::validation("The order has no shipping address"))?;
+        let international = !from.country.eq_ignore_ascii_case(&to.country);
+        let service_code = request
+            .service_code
+            .or_else(|| {
+                provider
+                    .get_services()
+                    .into_iter()
+                    .find(|service| if international { service.international } else { service.domestic })
+                    .map(|service| service.code)
+            })
+            .ok_or_else(|| Error::shipping(format!("{} has no service for shipping labels", provider.name())))?;
+        let package = Package::new(request.weight, request.weight_unit);
+
+        // Parcels going abroad carry a customs declaration of their items
+        let customs = if international {
+            Some(self.repository.customs(shipment.id).await?.to_customs_info(&from.country))
+        } else {
+            None
+        };
+
+        let label = provider
+            .create_shipment(&from, &to, &package, &service_code, customs.as_ref())
+            .await?;
+        let shipment = self
+            .repository
+            .set_label(
+                shipment.id,
+                provider.id(),
+                &service_code,
+                label.tracking_number.as_deref(),
+                label.label_url.as_deref(),
+            )
+            .await?;
+
+        // The label is paid for either way; without a stored copy it can
+        // still be printed from the carrier's link
+        match self.store_label(&shipment, &label).await {
+            Ok(Some((key, format))) => self.repository.set_label_file(shipment.id, &key, format).await,
+            Ok(None) => Ok(shipment),
+            Err(e) => {
+                tracing::warn!("Failed to store the label of shipment {}: {}", shipment.id, e);
+                Ok(shipment)
+            }
+        }
+    }
+
+    /// Keep a copy of a bought label; None without label storage or when
+    /// the carrier sent no label file
+    async fn store_label(&self, shipment: &Shipment, label: &CarrierShipment) -> Result<Option<(String, LabelFormat)>> {
+        let Some(storage) = &self.label_storage else {
+            return Ok(None);
+        };
+        let data = match (&label.label_data, &label.label_url) {
+            (Some(data), _) => STANDARD
+                .decode(data.trim())
+                .map_err(|e| Error::shipping(format!("The carrier sent an invalid label: {}", e)))?,
+            (None, Some(url)) => download_label(url).await?,
+            (None, None) => return Ok(None),
+        };
+        let format = LabelFormat::detect(&data)
+            .ok_or_else(|| Error::shipping("The carrier sent a label in an unknown format"))?;
+        // Local storage serves PNG files publicly, so keys aren't guessable
+        let key = format!("labels/{}/{}.{}", shipment.id, Uuid::new_v4().simple(), format.as_str());
+        storage.put(&key, data, format.content_type()).await?;
+        Ok(Some((key, format)))
+    }
+
+    fn label_storage(&self) -> Result<&Arc<dyn MediaStorage>> {
+        self.label_storage
+            .as_ref()
+            .ok_or_else(|| Error::shipping("Label storage is not configured"))
+    }
+
+    /// A shipment's stored label; with a format, fails if it is stored in another
+    pub async fn label(&self, shipment_id: Uuid, format: Option<LabelFormat>) -> Result<LabelFile> {
+        let storage = self.label_storage()?;
+        let shipment = self
+            .repository
+            .find_shipment(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))?;
+        let (Some(key), Some(stored)) = (&shipment.label_key, shipment.label_format) else {
+            return Err(Error::not_found("The shipment has no stored label"));
+        };
+        if format.is_some_and(|format| format != stored) {
+            return Err(Error::validation(format!(
+                "The carrier sent this label as {}",
+                stored.as_str().to_uppercase()
+            )));
+        }
+
+        Ok(LabelFile {
+            filename: format!("label-{}.{}", shipment.id, stored.as_str()),
+            content_type: stored.content_type(),
+            data: storage.get(key).await?,
+        })
+    }
+
+    /// The stored labels of a pick list of orders, in its order: one ZPL
+    /// document when they are all ZPL, else a ZIP of the label files.
+    /// Fails if an order has no stored label.
+    pub async fn batch_labels(&self, request: BatchLabelRequest) -> Result<LabelFile> {
+        let storage = self.label_storage()?;
+        let mut order_ids = Vec::with_capacity(request.order_ids.len());
+        for order_id in request.order_ids {
+            if !order_ids.contains(&order_id) {
+                order_ids.push(order_id);
+            }
+        }
+        if order_ids.is_empty() {
+            return Err(Error::validation("order_ids is required"));
+        }
+        if order_ids.len() > MAX_BATCH_LABEL_ORDERS {
+            return Err(Error::validation(format!(
+                "At most {} orders can be printed at once",
+                MAX_BATCH_LABEL_ORDERS
+            )));
+        }
+
+        let rows = self.repository.pick_list_labels(&order_ids).await?;
+        let unknown: Vec<String> = order_ids
+            .iter()
+            .filter(|order_id| !rows.iter().any(|row| row.order_id == **order_id))
+            .map(ToString::to_string)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(Error::not_found(format!("Orders not found: {}", unknown.join(", "))));
+        }
+        let unlabelled: Vec<&str> = rows
+            .iter()
+            .filter(|row| row.label_key.is_none())
+            .map(|row| row.order_number.as_str())
+            .collect();
+        if !unlabelled.is_empty() {
+            return Err(Error::validation(format!(
+                "Orders without a stored label: {}",
+                unlabelled.join(", ")
+            )));
+        }
+
+        let mut files = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let (Some(shipment_id), Some(key), Some(format)) = (row.shipment_id, &row.label_key, row.label_format) else {
+                continue;
+            };
+            let name = format!("{}-{}.{}", row.order_number.replace('/', "-"), shipment_id.simple(), format.as_str());
+            files.push((name, format, storage.get(key).await?));
+        }
+
+        if files.iter().all(|(_, format, _)| *format == LabelFormat::Zpl) {
+            let mut data = Vec::new();
+            for (_, _, label) in &files {
+                data.extend_from_slice(label);
+                if !label.ends_with(b"\n") {
+                    data.push(b'\n');
+                }
+            }
+            return Ok(LabelFile {
+                filename: "labels.zpl".to_string(),
+                content_type: LabelFormat::Zpl.content_type(),
+                data,
+            });
+        }
+        let entries: Vec<(&str, &[u8])> = files
+            .iter()
+            .map(|(name, _, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        Ok(LabelFile {
+            filename: "labels.zip".to_string(),
+            content_type: "application/zip",
+            data: zip_stored(&entries),
+        })
+    }
+
+    /// Set or clear an order's preference; it applies to what has not shipped
+    pub async fn set_order_preference(
+        &self,
+        order_id: Uuid,
+        customer_id: Option<Uuid>,
+        preference: Option<ShippingPreference>,
+    ) -> Result<ShippingPreference> {
+        let order = self.get_order(order_id).await?;
+        // Customers can only change their own orders
+        if customer_id.is_some_and(|customer_id| order.customer_id != Some(customer_id)) {
+            return Err(Error::not_found("Order not found"));
+        }
+        if !order.can_ship() && order.status != "pending" {
+            return Err(Error::validation(format!(
+                "Orders that are {} have no shipments left to plan",
+                order.status
+            )));
+        }
+        self.repository.set_order_preference(order_id, preference).await?;
+        Ok(preference
+            .or(order.customer_preference)
+            .unwrap_or(self.config.default_shipping_preference))
+    }
+
+    /// Set or clear a customer's preference for their future orders
+    pub async fn set_customer_preference(
+        &self,
+        customer_id: Uuid,
+        preference: Option<ShippingPreference>,
+    ) -> Result<ShippingPreference> {
+        if !self.repository.set_customer_preference(customer_id, preference).await? {
+            return Err(Error::not_found("Customer not found"));
+        }
+        Ok(preference.unwrap_or(self.config.default_shipping_preference))
+    }
+
+    /// Ship backordered units that are back in stock, oldest orders first
+    pub async fn release_backorders(&self) -> Result<BackorderReport> {
+        let mut report = BackorderReport::default();
+        for order_id in self.repository.backordered_orders(RELEASE_BATCH).await? {
+            report.orders_checked += 1;
+            match self.release_order(order_id).await {
+                Ok((shipments, complete)) => {
+                    report.shipments_created += shipments;
+                    if complete {
+                        report.orders_completed += 1;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to ship backorders of order {}: {}", order_id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn release_order(&self, order_id: Uuid) -> Result<(usize, bool)> {
+        let order = self.get_order(order_id).await?;
+        let (plan, lines) = self.plan(&order).await?;
+        if plan.shipments.is_empty() {
+            return Ok((0, false));
+        }
+        let created = self.repository.apply_plan(&order, &plan, true).await?;
+        self.notify_backorder_shipped(&order, &plan, &lines).await;
+        Ok((created.len(), plan.backordered.is_empty()))
+    }
+
+    fn recipient(&self, order: &ShippingOrder) -> Option<Recipient> {
+        if self.notifications.is_none() || !self.config.notify_customers || !order.email_notifications {
+            return None;
+        }
+        Some(Recipient::email(order.email.clone(), None))
+    }
+
+    async fn notify_split(&self, order: &ShippingOrder, plan: &FulfillmentPlan, lines: &[OpenOrderLine]) {
+        let (Some(notifications), Some(recipient)) = (&self.notifications, self.recipient(order)) else {
+            return;
+        };
+        let notification = NotificationFactory::order_split(
+            order.id,
+            &order.order_number,
+            plan.shipments.len(),
+            &describe_lines(plan.shipments.iter().flat_map(|shipment| &shipment.lines), lines),
+            &describe_lines(&plan.backordered, lines),
+            recipient,
+        );
+        if let Err(e) = notifications.create(&notification).await {
+            tracing::warn!("Failed to queue split notice for order {}: {}", order.order_number, e);
+        }
+    }
+
+    async fn notify_backorder_shipped(&self, order: &ShippingOrder, plan: &FulfillmentPlan, lines: &[OpenOrderLine]) {
+        if plan.shipments.is_empty() {
+            return;
+        }
+        let (Some(notifications), Some(recipient)) = (&self.notifications, self.recipient(order)) else {
+            return;
+        };
+        let notification = NotificationFactory::backorder_shipped(
+            order.id,
+            &order.order_number,
+            &describe_lines(plan.shipments.iter().flat_map(|shipment| &shipment.lines), lines),
+            &describe_lines(&plan.backordered, lines),
+            recipient,
+        );
+        if let Err(e) = notifications.create(&notification).await {
+            tracing::warn!("Failed to queue backorder notice for order {}: {}", order.order_number, e);
+        }
+    }
+}
+
+/// "2 x Title" per line, units of the same line added up
+fn describe_lines<'a>(planned: impl IntoIterator<Item = &'a PlannedLine>, lines: &[OpenOrderLine]) -> Vec<String> {
+    let mut merged = Vec::new();
+    for line in planned {
+        add_line(&mut merged, line.order_item_id, line.quantity);
+    }
+    merged
+        .iter()
+        .map(|planned| {
+            let title = lines
+                .iter()
+                .find(|line| line.order_item_id == planned.order_item_id)
+                .map_or("Item", |line| line.title.as_str());
+            format!("{} x {}", planned.quantity, title)
+        })
+        .collect()
+}
+
+/// Recurring job shipping backorders that are back in stock
+pub struct BackorderJob<R: ShipmentRepository> {
+    shipments: Arc<ShipmentService<R>>,
+}
+
+impl<R: ShipmentRepository> BackorderJob<R> {
+    pub fn new(shipments: Arc<ShipmentService<R>>) -> Self {
+        Self { shipments }
+    }
+}
+
+#[async_trait]
+impl<R: ShipmentRepository + 'static> RecurringJob for BackorderJob<R> {
+    fn name(&self) -> &str {
+        "backorders"
+    }
+
+    fn description(&self) -> &str {
+        "Ship backordered order items that are back in stock"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.shipments.config().backorder_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.shipments.release_backorders().await?.to_string())
+    }
+}
+
+/// Download a label the carrier only sent a link to
+async fn download_label(url: &str) -> Result<Vec<u8>> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(LABEL_DOWNLOAD_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(id: u128, product: u128, quantity: i32) -> OpenOrderLine {
+        OpenOrderLine {
+            order_item_id: Uuid::from_u128(id),
+            product_id: Uuid::from_u128(product),
+            variant_id: None,
+            title: format!("Product {}", product),
+            quantity,
+        }
+    }
+
+    fn level(location: u128, product: u128, available: i32) -> LocationStock {
+        LocationStock {
+            location_id: Uuid::from_u128(location),
+            product_id: Uuid::from_u128(product),
+            variant_id: None,
+            available,
+        }
+    }
+
+    fn planned(id: u128, quantity: i32) -> PlannedLine {
+        PlannedLine {
+            order_item_id: Uuid::from_u128(id),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_plan_prefers_one_location_with_everything() {
+        let lines = [line(1, 10, 2), line(2, 20, 1)];
+        let stock = [level(100, 10, 5), level(200, 10, 5), level(200, 20, 1)];
+
+        let plan = plan_fulfillment(&lines, &stock, ShippingPreference::ShipPartial);
+        assert_eq!(
+            plan.shipments,
+            vec![PlannedShipment {
+                location_id: Uuid::from_u128(200),
+                lines: vec![planned(1, 2), planned(2, 1)],
+            }]
+        );
+        assert!(plan.backordered.is_empty());
+        assert!(!plan.is_split());
+    }
+
+    #[test]
+    fn test_plan_splits_across_locations_and_backorders_the_rest() {
+        let lines = [line(1, 10, 3), line(2, 20, 2)];
+        let stock = [level(100, 10, 3), level(200, 20, 1)];
+
+        let plan = plan_fulfillment(&lines, &stock, ShippingPreference::ShipPartial);
+        assert_eq!(plan.shipments.len(), 2);
+        assert_eq!(plan.shipments[0].location_id, Uuid::from_u128(100));
+        assert_eq!(plan.shipments[0].lines, vec![planned(1, 3)]);
+        assert_eq!(plan.shipments[1].lines, vec![planned(2, 1)]);
+        assert_eq!(plan.backordered, vec![planned(2, 1)]);
+        assert!(plan.is_split());
+    }
+
+    #[test]
+    fn test_plan_ship_complete_holds_the_order() {
+        let lines = [line(1, 10, 1), line(2, 20, 2)];
+        let stock = [level(100, 10, 5), level(100, 20, 1)];
+
+        let plan = plan_fulfillment(&lines, &stock, ShippingPreference::ShipComplete);
+        assert!(plan.shipments.is_empty());
+        assert_eq!(plan.backordered, vec![planned(1, 1), planned(2, 2)]);
+        assert!(!plan.is_split());
+
+        // Still split across locations when everything is in stock
+        let stock = [level(100, 10, 5), level(200, 20, 2)];
+        let plan = plan_fulfillment(&lines, &stock, ShippingPreference::ShipComplete);
+        assert_eq!(plan.shipments.len(), 2);
+        assert!(plan.backordered.is_empty());
+    }
+
+    #[test]
+    fn test_plan_shares_stock_between_lines_of_one_product() {
+        let lines = [line(1, 10, 2), line(2, 10, 2)];
+        let stock = [level(100, 10, 3)];
+
+        let plan = plan_fulfillment(&lines, &stock, ShippingPreference::ShipPartial);
+        assert_eq!(plan.shipments[0].lines, vec![planned(1, 2), planned(2, 1)]);
+        assert_eq!(plan.backordered, vec![planned(2, 1)]);
+    }
+
+    #[test]
+    fn test_plan_without_stock() {
+        let lines = [line(1, 10, 1)];
+        let plan = plan_fulfillment(&lines, &[level(100, 10, 0)], ShippingPreference::ShipPartial);
+        assert!(plan.shipments.is_empty());
+        assert_eq!(plan.backordered, vec![planned(1, 1)]);
+    }
+
+    #[test]
+    fn test_preference_order() {
+        let mut order = ShippingOrder {
+            id: Uuid::nil(),
+            order_number: "1001".to_string(),
+            email: "a@example.com".to_string(),
+            customer_id: None,
+            status: "confirmed".to_string(),
+            order_preference: None,
+            customer_preference: Some(ShippingPreference::ShipComplete),
+            email_notifications: true,
+            pickup_location_id: None,
+        };
+        assert_eq!(order.preference(ShippingPreference::ShipPartial), ShippingPreference::ShipComplete);
+        order.order_preference = Some(ShippingPreference::ShipPartial);
+        assert_eq!(order.preference(ShippingPreference::ShipComplete), ShippingPreference::ShipPartial);
+        order.customer_preference = None;
+        order.order_preference = None;
+        assert_eq!(order.preference(ShippingPreference::ShipComplete), ShippingPreference::ShipComplete);
+    }
+
+    #[test]
+    fn test_describe_lines() {
+        let lines = [line(1, 10, 3)];
+        assert_eq!(
+            describe_lines(&[planned(1, 1), planned(1, 2)], &lines),
+            vec!["3 x Product 10".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_customs_info_of_shipment() {
+        let line = |description: &str, value: Decimal, origin: Option<&str>| CustomsLine {
+            description: description.to_string(),
+            quantity: 2,
+            value,
+            weight: None,
+            weight_unit: None,
+            hs_code: Some("610910".to_string()),
+            origin_country: origin.map(str::to_string),
+        };
+        let customs = ShipmentCustoms {
+            incoterm: Some(Incoterm::Ddp),
+            currency: "EUR".to_string(),
+            lines: vec![
+                line("T-shirt", Decimal::from(40), Some("PT")),
+                line("T-shirt", Decimal::from(30), None),
+            ],
+        };
+
+        let info = customs.to_customs_info("de");
+        assert_eq!(info.contents_description, "T-shirt");
+        assert_eq!(info.declaration_value, Decimal::from(70));
+        assert_eq!(info.incoterm, Some(Incoterm::Ddp));
+        assert_eq!(info.customs_items[0].origin_country, "PT");
+        assert_eq!(info.customs_items[1].origin_country, "DE");
+    }
+
+    #[test]
+    fn test_label_format_detection() {
+        assert_eq!(LabelFormat::detect(b"%PDF-1.4\n..."), Some(LabelFormat::Pdf));
+        assert_eq!(LabelFormat::detect(b"\n^XA^FO50,50^FDLabel^FS^XZ"), Some(LabelFormat::Zpl));
+        assert_eq!(LabelFormat::detect(b"\x89PNG\r\n\x1a\n"), Some(LabelFormat::Png));
+        assert_eq!(LabelFormat::detect(b"<html>"), None);
+        assert_eq!(LabelFormat::Zpl.content_type(), "application/zpl");
+    }
+}
@@ -2,12 +2,30 @@
 //! 
 //! Provides a unified interface for all payment operations regardless of the gateway.
 //! The frontend interacts with our API only - never directly with payment providers.
+//!
+//! Card payments that need Strong Customer Authentication answer with a
+//! `three_d_secure` action whose data is a `ThreeDsChallenge`: the card
+//! issuer's page to redirect to (or frame), or a client secret for the
+//! gateway's JS SDK. Gateways send the customer back to the request's
+//! `return_url` afterwards, where the payment is completed.
+//!
+//! Payments requested with `CaptureMethod::Manual` are only authorized
+//! (`PaymentStatus::Authorized`); gateways that support it capture them
+//! later, in one or several parts, or void them. Others charge at once.
+//!
+//! Gateways that report payouts list them with the balance transactions
+//! each settled, for `PayoutReconciliationService` to match against
+//! recorded payments and refunds.
+
+use std::collections::BTreeMap;
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::HostedCheckoutMode;
+use crate::payment::wallet::{DecryptedWalletToken, WalletTokenDecryptor, WalletType};
 use crate::Result;
 
 /// Payment method types supported by a gateway
@@ -66,6 +84,15 @@ impl PaymentMethodType {
             PaymentMethodType::CashOnDelivery => "cash",
         }
     }
+    
+    /// The wallet behind this method, for Apple Pay and Google Pay
+    pub fn wallet(&self) -> Option<WalletType> {
+        match self {
+            PaymentMethodType::ApplePay => Some(WalletType::ApplePay),
+            PaymentMethodType::GooglePay => Some(WalletType::GooglePay),
+            _ => None,
+        }
+    }
 }
 
 /// Payment method configuration for a gateway
@@ -199,6 +226,23 @@ pub struct InitiatePaymentRequest {
     pub description: String,
     /// Metadata for the payment
     pub metadata: serde_json::Value,
+    /// Where the gateway sends the customer after a 3-D Secure challenge
+    #[serde(default)]
+    pub return_url: Option<String>,
+    /// Charge at once, or only authorize for later captures
+    #[serde(default)]
+    pub capture_method: CaptureMethod,
+}
+
+/// When an initiated payment is captured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMethod {
+    /// Captured as soon as it is authorized
+    #[default]
+    Automatic,
+    /// Only authorized; captured later with `capture_payment`
+    Manual,
 }
 
 /// Payment method data - varies by type
@@ -226,10 +270,18 @@ pub enum PaymentMethodData {
     /// Digital wallet data
     DigitalWallet {
         /// Wallet type
-        wallet_type: String,
-        /// Payment token from wallet
+        wallet_type: WalletType,
+        /// Encrypted payment token from the wallet: the Apple Pay
+        /// `PKPaymentToken` or Google Pay `tokenizationData.token` JSON (or a
+        /// gateway token/payment method ID from the gateway's JS SDK)
         token: String,
     },
+    /// Wallet token decrypted by the `WalletTokenDecryptor`; never accepted
+    /// from clients
+    #[serde(skip_deserializing)]
+    DecryptedWallet {
+        token: DecryptedWalletToken,
+    },
     /// Bank transfer data
     BankTransfer {
         /// Account number
@@ -312,6 +364,94 @@ pub enum PaymentActionType {
     Challenge,
 }
 
+/// How the storefront shows a 3-D Secure challenge
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeDisplay {
+    /// Send the customer to the issuer's page
+    #[default]
+    Redirect,
+    /// Load the issuer's page in an iframe
+    Iframe,
+    /// Let the gateway's JS SDK run the challenge
+    Sdk,
+}
+
+/// Action data of a `three_d_secure` action
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ThreeDsChallenge {
+    pub display: ChallengeDisplay,
+    /// Issuer's challenge page (redirect and iframe)
+    pub url: Option<String>,
+    /// Fields to POST to `url` (e.g. `creq`); it is a GET when empty
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub form: BTreeMap<String, String>,
+    /// Secret for the gateway's JS SDK (sdk)
+    pub client_secret: Option<String>,
+}
+
+impl ThreeDsChallenge {
+    /// Challenge on the issuer's page at `url`
+    pub fn redirect(url: impl Into<String>) -> Self {
+        Self {
+            display: ChallengeDisplay::Redirect,
+            url: Some(url.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Challenge run by the gateway's JS SDK
+    pub fn sdk(client_secret: impl Into<String>) -> Self {
+        Self {
+            display: ChallengeDisplay::Sdk,
+            client_secret: Some(client_secret.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Shown as the storefront asked, if the challenge has a page to show
+    pub fn displayed_as(mut self, display: ChallengeDisplay) -> Self {
+        if display != ChallengeDisplay::Sdk && self.url.is_some() {
+            self.display = display;
+        }
+        self
+    }
+
+    pub fn action_data(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// The challenge in a gateway's action data: a `ThreeDsChallenge`, a
+    /// `next_action` with `url`, `method` and `data`, a `redirect_url` (or
+    /// `{ "url": ... }`), or a bare `client_secret`
+    pub fn from_action_data(data: &serde_json::Value) -> Option<Self> {
+        if let Ok(challenge) = serde_json::from_value::<Self>(data.clone()) {
+            if challenge.url.is_some() || challenge.client_secret.is_some() {
+                return Some(challenge);
+            }
+        }
+        let str_of = |value: &serde_json::Value| -> Option<String> {
+            value.as_str().or_else(|| value.get("url")?.as_str()).map(str::to_string)
+        };
+        if let Some(next_action) = data.get("next_action").filter(|action| action.get("url").is_some()) {
+            let mut challenge = Self::redirect(str_of(&next_action["url"])?);
+            if next_action["method"].as_str().is_some_and(|method| method.eq_ignore_ascii_case("post")) {
+                if let Some(fields) = next_action["data"].as_object() {
+                    challenge.form = fields
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.as_str().map_or_else(|| value.to_string(), str::to_string)))
+                        .collect();
+                }
+            }
+            return Some(challenge);
+        }
+        if let Some(url) = data.get("redirect_url").and_then(str_of) {
+            return Some(Self::redirect(url));
+        }
+        data.get("client_secret").and_then(|secret| secret.as_str()).map(Self::sdk)
+    }
+}
+
 /// Payment status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -322,6 +462,8 @@ pub enum PaymentStatus {
     Processing,
     /// Payment requires action
     RequiresAction,
+    /// Funds held, waiting to be captured
+    Authorized,
     /// Payment succeeded
     Succeeded,
     /// Payment failed
@@ -404,6 +546,44 @@ pub enum CompletePaymentActionResponse {
     },
 }
 
+/// Request for a hosted payment page for an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostedCheckoutRequest {
+    pub mode: HostedCheckoutMode,
+    pub order_id: Uuid,
+    pub amount: Decimal,
+    pub currency: String,
+    pub customer_email: Option<String>,
+    /// Shown on the payment page, e.g. "Order #1001"
+    pub description: String,
+    pub success_url: String,
+    pub cancel_url: Option<String>,
+    /// When a checkout session expires (payment links don't)
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A hosted payment page created at the gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostedCheckoutPage {
+    /// Checkout session or payment link ID
+    pub provider_id: String,
+    pub url: String,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Details of a hosted checkout webhook event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostedCheckoutEvent {
+    /// Checkout session ID
+    pub checkout_id: String,
+    /// Payment link the session was opened from
+    pub link_id: Option<String>,
+    /// Gateway payment that paid the session
+    pub payment_id: Option<String>,
+    pub amount: Option<Decimal>,
+    pub currency: Option<String>,
+}
+
 /// Unified payment gateway trait
 #[async_trait]
 pub trait AgnosticPaymentGateway: Send + Sync {
@@ -454,6 +634,48 @@ pub trait AgnosticPaymentGateway: Send + Sync {
     
     /// Delete a saved payment method
     async fn delete_payment_method(&self, token: &str) -> Result<()>;
+    
+    /// Capture part of an authorized payment; `final_capture` releases the
+    /// rest of the authorization
+    async fn capture_payment(&self, _payment_id: &str, _amount: Decimal, _final_capture: bool) -> Result<CaptureResponse> {
+        Err(crate::Error::validation("This gateway does not support capturing authorized payments"))
+    }
+    
+    /// Release an authorized payment that won't be captured
+    async fn void_payment(&self, _payment_id: &str) -> Result<()> {
+        Err(crate::Error::validation("This gateway does not support voiding authorized payments"))
+    }
+    
+    /// Payouts created since `since`, with their transactions
+    async fn list_payouts(&self, _since: chrono::DateTime<chrono::Utc>) -> Result<Vec<GatewayPayout>> {
+        Err(crate::Error::validation("This gateway does not report payouts"))
+    }
+    
+    /// Create a hosted payment page (checkout session or payment link)
+    async fn create_hosted_checkout(&self, _request: HostedCheckoutRequest) -> Result<HostedCheckoutPage> {
+        Err(crate::Error::validation("This gateway does not support hosted checkout"))
+    }
+    
+    /// Close a hosted payment page so it can no longer be paid
+    async fn expire_hosted_checkout(&self, _mode: HostedCheckoutMode, _provider_id: &str) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Details of a `Checkout*` webhook event from this gateway
+    fn hosted_checkout_event(&self, _event: &WebhookEvent) -> Option<HostedCheckoutEvent> {
+        None
+    }
+    
+    /// Whether encrypted wallet tokens can be sent as is; otherwise they are
+    /// decrypted first by the payment service's `WalletTokenDecryptor`
+    fn accepts_encrypted_wallet_tokens(&self, _wallet: WalletType) -> bool {
+        false
+    }
+    
+    /// Register a storefront domain for Apple Pay with the gateway
+    async fn register_apple_pay_domain(&self, _domain: &str) -> Result<()> {
+        Err(crate::Error::validation("This gateway does not support Apple Pay domain registration"))
+    }
 }
 
 /// Refund response
@@ -468,6 +690,56 @@ pub struct RefundResponse {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Result of capturing an authorized payment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureResponse {
+    pub capture_id: String,
+    pub amount: Decimal,
+    /// `Authorized` while more can be captured, `Succeeded` once the rest is released
+    pub status: PaymentStatus,
+}
+
+/// A payout from a gateway to the merchant's bank account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayPayout {
+    pub payout_id: String,
+    /// Net amount paid out
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    pub arrival_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub transactions: Vec<SettlementTransaction>,
+}
+
+/// What a balance transaction settled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "settlement_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementKind {
+    Charge,
+    Refund,
+    /// Gateway fees not tied to a charge
+    Fee,
+    /// Disputes, reserves and other adjustments
+    Adjustment,
+}
+
+/// A balance transaction included in a payout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementTransaction {
+    pub transaction_id: String,
+    pub kind: SettlementKind,
+    /// The gateway payment or refund ID it settles
+    pub reference: Option<String>,
+    /// Gross amount; negative for refunds
+    pub amount: Decimal,
+    pub fee: Decimal,
+    pub net: Decimal,
+    pub currency: String,
+    pub description: Option<String>,
+}
+
 /// Refund status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -513,12 +785,19 @@ pub enum WebhookEventType {
     SubscriptionCancelled,
     SubscriptionPaymentSucceeded,
     SubscriptionPaymentFailed,
+    /// A hosted checkout was paid
+    CheckoutCompleted,
+    /// A hosted checkout's delayed payment failed
+    CheckoutFailed,
+    /// A hosted checkout expired unpaid
+    CheckoutExpired,
 }
 
 /// Payment service - orchestrates payments across multiple gateways
 pub struct PaymentService {
     gateways: std::collections::HashMap<String, Box<dyn AgnosticPaymentGateway>>,
     default_gateway: String,
+    wallet_decryptor: Option<std::sync::Arc<dyn WalletTokenDecryptor>>,
 }
 
 impl PaymentService {
@@ -526,9 +805,15 @@ impl PaymentService {
         Self {
             gateways: std::collections::HashMap::new(),
             default_gateway,
+            wallet_decryptor: None,
         }
     }
     
+    /// Decrypt wallet tokens for gateways that don't accept them encrypted
+    pub fn set_wallet_decryptor(&mut self, decryptor: std::sync::Arc<dyn WalletTokenDecryptor>) {
+        self.wallet_decryptor = Some(decryptor);
+    }
+    
     pub fn register_gateway(
         &mut self,
         gateway_id: String,
@@ -537,11 +822,47 @@ impl PaymentService {
         self.gateways.insert(gateway_id, gateway);
     }
     
+    /// ID of the gateway used when none is given
+    pub fn default_gateway(&self) -> &str {
+        &self.default_gateway
+    }
+    
     pub fn get_gateway(&self, gateway_id: Option<&str>) -> Option<&dyn AgnosticPaymentGateway> {
         let id = gateway_id.unwrap_or(&self.default_gateway);
         self.gateways.get(id).map(|g| g.as_ref())
     }
     
+    /// Check a payment request before it goes to the gateway, decrypting a
+    /// wallet token the gateway can't take encrypted
+    pub async fn prepare_payment(
+        &self,
+        gateway: &dyn AgnosticPaymentGateway,
+        mut request: InitiatePaymentRequest,
+    ) -> Result<InitiatePaymentRequest> {
+        let PaymentMethodData::DigitalWallet { wallet_type, ref token } = request.payment_method_data else {
+            return Ok(request);
+        };
+        if request.payment_method_type.wallet() != Some(wallet_type) {
+            return Err(crate::Error::validation("Wallet token does not match payment_method_type"));
+        }
+        if gateway.accepts_encrypted_wallet_tokens(wallet_type) {
+            return Ok(request);
+        }
+        
+        let decryptor = self.wallet_decryptor.as_ref().ok_or_else(|| {
+            crate::Error::validation(format!(
+                "This gateway does not accept {} tokens",
+                wallet_type.method_type().display_name()
+            ))
+        })?;
+        let decrypted = decryptor.decrypt(wallet_type, token).await?;
+        if decrypted.wallet != wallet_type {
+            return Err(crate::Error::payment_error("Decrypted wallet token is for a different wallet"));
+        }
+        request.payment_method_data = PaymentMethodData::DecryptedWallet { token: decrypted };
+        Ok(request)
+    }
+    
     /// Get available payment methods across all gateways
     pub async fn get_available_payment_methods(
         &self,
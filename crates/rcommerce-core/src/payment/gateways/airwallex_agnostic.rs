@@ -12,6 +12,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::Result;
 use crate::payment::agnostic::*;
+use crate::payment::wallet::WalletType;
 
 const AIRWALLEX_API_BASE_PROD: &str = "https://api.airwallex.com/api/v1";
 const AIRWALLEX_API_BASE_DEMO: &str = "https://api-demo.airwallex.com/api/v1";
@@ -205,6 +206,7 @@ impl AirwallexAgnosticGateway {
         match status {
             "REQUIRES_ACTION" | "PENDING" => PaymentStatus::Pending,
             "PROCESSING" => PaymentStatus::Processing,
+            "REQUIRES_CAPTURE" => PaymentStatus::Authorized,
             "SUCCEEDED" | "CAPTURED" => PaymentStatus::Succeeded,
             "FAILED" | "CANCELLED" => PaymentStatus::Failed,
             "REFUNDED" | "PARTIALLY_REFUNDED" => PaymentStatus::Refunded,
@@ -383,6 +385,15 @@ impl AgnosticPaymentGateway for AirwallexAgnosticGateway {
                 let confirmed_intent = self.confirm_payment_intent_with_token(&intent.id, &token).await?;
                 return self.handle_intent_response(confirmed_intent, &request.currency);
             }
+            PaymentMethodData::DigitalWallet { wallet_type, token } => {
+                let payment_method = wallet_payment_method(*wallet_type, token)?;
+                let confirmed_intent = self.confirm_payment_intent_with_wallet(&intent.id, payment_method).await?;
+                let mut response = self.handle_intent_response(confirmed_intent, &request.currency)?;
+                if let InitiatePaymentResponse::Success { ref mut payment_method, .. } = response {
+                    payment_method.method_type = wallet_type.method_type();
+                }
+                return Ok(response);
+            }
             _ => None,
         };
 
@@ -627,10 +638,66 @@ impl AgnosticPaymentGateway for AirwallexAgnosticGateway {
 
         Ok(())
     }
+
+    fn accepts_encrypted_wallet_tokens(&self, _wallet: WalletType) -> bool {
+        true
+    }
+
+    async fn register_apple_pay_domain(&self, domain: &str) -> Result<()> {
+        let token = self.get_access_token().await?;
+
+        let response = self.client
+            .post(format!("{}/pa/config/applepay/registered_domains/add_items", self.base_url))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "items": [domain] }))
+            .send()
+            .await
+            .map_err(|e| crate::Error::network(format!("Airwallex API error: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(crate::Error::payment_error(format!("Airwallex error: {}", error_text)));
+        }
+
+        Ok(())
+    }
 }
 
 // Helper methods
 impl AirwallexAgnosticGateway {
+    async fn confirm_payment_intent_with_wallet(
+        &self,
+        intent_id: &str,
+        payment_method: serde_json::Value,
+    ) -> Result<AirwallexPaymentIntent> {
+        let access_token = self.get_access_token().await?;
+
+        let confirm_payload = serde_json::json!({
+            "request_id": uuid::Uuid::new_v4().to_string(),
+            "payment_method": payment_method,
+        });
+
+        let response = self.client
+            .post(format!("{}/pa/payment_intents/{}/confirm", self.base_url, intent_id))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Content-Type", "application/json")
+            .json(&confirm_payload)
+            .send()
+            .await
+            .map_err(|e| crate::Error::network(format!("Airwallex confirm error: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(crate::Error::payment_error(format!("Airwallex confirm error: {}", error_text)));
+        }
+
+        let intent: AirwallexPaymentIntent = response.json().await
+            .map_err(|e| crate::Error::network(format!("Failed to parse Airwallex response: {}", e)))?;
+
+        Ok(intent)
+    }
+
     async fn confirm_payment_intent_with_token(
         &self,
         intent_id: &str,
@@ -790,6 +857,40 @@ struct CardData {
     name: String,
 }
 
+/// Airwallex payment method for an encrypted wallet token
+fn wallet_payment_method(wallet: WalletType, token: &str) -> Result<serde_json::Value> {
+    match wallet {
+        WalletType::GooglePay => {
+            // Google Pay's tokenizationData.token is passed on as a string
+            serde_json::from_str::<serde_json::Value>(token)
+                .map_err(|_| crate::Error::validation("Invalid Google Pay payment token"))?;
+            Ok(serde_json::json!({
+                "type": "googlepay",
+                "googlepay": {
+                    "payment_data_type": "encrypted_payment_token",
+                    "encrypted_payment_token": token,
+                },
+            }))
+        }
+        WalletType::ApplePay => {
+            // A full PKPaymentToken, or just its paymentData
+            let value: serde_json::Value = serde_json::from_str(token)
+                .map_err(|_| crate::Error::validation("Invalid Apple Pay payment token"))?;
+            let payment_data = value.get("paymentData").cloned().unwrap_or(value);
+            if payment_data.get("data").is_none() || payment_data.get("header").is_none() {
+                return Err(crate::Error::validation("Invalid Apple Pay payment token"));
+            }
+            Ok(serde_json::json!({
+                "type": "applepay",
+                "applepay": {
+                    "payment_data_type": "encrypted_payment_token",
+                    "encrypted_payment_token": payment_data,
+                },
+            }))
+        }
+    }
+}
+
 // Airwallex API response types
 #[derive(Debug, Serialize, Deserialize)]
 struct AirwallexAuthResponse {
@@ -885,12 +986,30 @@ mod tests {
         assert_eq!(AirwallexAgnosticGateway::map_status("PENDING"), PaymentStatus::Pending);
         assert_eq!(AirwallexAgnosticGateway::map_status("PROCESSING"), PaymentStatus::Processing);
         assert_eq!(AirwallexAgnosticGateway::map_status("SUCCEEDED"), PaymentStatus::Succeeded);
+        assert_eq!(AirwallexAgnosticGateway::map_status("REQUIRES_CAPTURE"), PaymentStatus::Authorized);
         assert_eq!(AirwallexAgnosticGateway::map_status("CAPTURED"), PaymentStatus::Succeeded);
         assert_eq!(AirwallexAgnosticGateway::map_status("FAILED"), PaymentStatus::Failed);
         assert_eq!(AirwallexAgnosticGateway::map_status("CANCELLED"), PaymentStatus::Failed);
         assert_eq!(AirwallexAgnosticGateway::map_status("REFUNDED"), PaymentStatus::Refunded);
     }
 
+    #[test]
+    fn test_wallet_payment_method() {
+        let google = wallet_payment_method(WalletType::GooglePay, r#"{"protocolVersion":"ECv2","signature":"s"}"#).unwrap();
+        assert_eq!(google["type"], "googlepay");
+        assert_eq!(google["googlepay"]["encrypted_payment_token"], r#"{"protocolVersion":"ECv2","signature":"s"}"#);
+        assert!(wallet_payment_method(WalletType::GooglePay, "not json").is_err());
+
+        let pk_token = serde_json::json!({
+            "paymentData": {"version": "EC_v1", "data": "abc", "signature": "sig", "header": {"transactionId": "t1"}},
+            "transactionIdentifier": "t1",
+        });
+        let apple = wallet_payment_method(WalletType::ApplePay, &pk_token.to_string()).unwrap();
+        assert_eq!(apple["type"], "applepay");
+        assert_eq!(apple["applepay"]["encrypted_payment_token"], pk_token["paymentData"]);
+        assert!(wallet_payment_method(WalletType::ApplePay, r#"{"transactionIdentifier":"t1"}"#).is_err());
+    }
+
     #[test]
     fn test_map_refund_status() {
         assert_eq!(AirwallexAgnosticGateway::map_refund_status("PENDING"), RefundStatus::Pending);
@@ -345,6 +345,10 @@ impl AgnosticPaymentGateway for MockPaymentGateway {
     async fn delete_payment_method(&self, _token: &str) -> Result<()> {
         Ok(())
     }
+
+    fn accepts_encrypted_wallet_tokens(&self, _wallet: crate::payment::WalletType) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
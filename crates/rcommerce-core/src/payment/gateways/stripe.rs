@@ -68,7 +68,12 @@ impl PaymentGateway for StripeGateway {
         if let Some(customer_id) = request.customer_id {
             params.insert("metadata[customer_id]", customer_id.to_string());
         }
-        
+
+        // Orders flagged by fraud screening must authenticate with 3-D Secure
+        if request.metadata.get("three_d_secure").and_then(|v| v.as_str()) == Some("required") {
+            params.insert("payment_method_options[card][request_three_d_secure]", "any".to_string());
+        }
+
         // Add customer email for receipt
         if !request.customer_email.is_empty() {
             params.insert("receipt_email", request.customer_email);
@@ -1,20 +1,30 @@
 //! Stripe Payment Gateway - Agnostic Implementation
 //! 
 //! Server-to-server implementation that handles card data securely without exposing keys to frontend.
+//! Payment intents are confirmed with the request's `return_url`, so 3-D
+//! Secure challenges come back as the issuer's page to redirect to.
+//! Payouts are listed with the balance transactions each one paid out.
 
 use async_trait::async_trait;
+use hmac::{Hmac, Mac};
 use reqwest;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use serde_json::json;
+use sha2::Sha256;
 
+use crate::models::HostedCheckoutMode;
 use crate::Result;
 use crate::payment::agnostic::*;
+use crate::payment::wallet::WalletType;
+
+/// Webhook signatures older than this are rejected (Stripe's default tolerance)
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
 
 pub struct StripeAgnosticGateway {
     api_key: String,
-    #[allow(dead_code)]
     webhook_secret: String,
     client: reqwest::Client,
     supported_methods: Vec<PaymentMethodConfig>,
@@ -180,6 +190,7 @@ impl StripeAgnosticGateway {
         customer_email: &str,
         description: &str,
         metadata: serde_json::Value,
+        options: Vec<(&'static str, String)>,
     ) -> Result<StripePaymentIntent> {
         let amount_in_cents: i64 = (amount * dec!(100)).try_into().unwrap_or(0i64);
         
@@ -188,7 +199,6 @@ impl StripeAgnosticGateway {
             ("currency", currency.to_lowercase()),
             ("payment_method", payment_method_id.to_string()),
             ("confirmation_method", "manual".to_string()),
-            ("capture_method", "automatic".to_string()),
             ("receipt_email", customer_email.to_string()),
             ("description", description.to_string()),
             ("confirm", "true".to_string()),
@@ -198,6 +208,7 @@ impl StripeAgnosticGateway {
         if let Some(order_id) = metadata.get("order_id").and_then(|v| v.as_str()) {
             params.push(("metadata[order_id]", order_id.to_string()));
         }
+        params.extend(options);
         
         let response = self.client
             .post("https://api.stripe.com/v1/payment_intents")
@@ -255,16 +266,46 @@ impl StripeAgnosticGateway {
             "requires_confirmation" => PaymentStatus::Pending,
             "requires_action" => PaymentStatus::RequiresAction,
             "processing" => PaymentStatus::Processing,
+            "requires_capture" => PaymentStatus::Authorized,
             "succeeded" => PaymentStatus::Succeeded,
             "canceled" => PaymentStatus::Cancelled,
             _ => PaymentStatus::Failed,
         }
     }
     
+    /// Create a Stripe payment method from a wallet token
+    async fn create_wallet_payment_method(&self, wallet: WalletType, token: &str) -> Result<String> {
+        let card_token = match parse_wallet_token(wallet, token)? {
+            StripeWalletToken::PaymentMethod(id) => return Ok(id),
+            StripeWalletToken::CardToken(id) => id,
+            StripeWalletToken::ApplePay(params) => {
+                let token: StripeObject = self.post_form("https://api.stripe.com/v1/tokens", &params).await?;
+                token.id
+            }
+        };
+        
+        let payment_method: StripeObject = self
+            .post_form(
+                "https://api.stripe.com/v1/payment_methods",
+                &[("type", "card".to_string()), ("card[token]", card_token)],
+            )
+            .await?;
+        Ok(payment_method.id)
+    }
+    
+    /// Card, or the wallet the card was used through
+    fn method_type(payment_method: &StripePaymentMethod) -> PaymentMethodType {
+        match payment_method.card.as_ref().and_then(|c| c.wallet.as_ref()).map(|w| w.type_.as_str()) {
+            Some("apple_pay") => PaymentMethodType::ApplePay,
+            Some("google_pay") => PaymentMethodType::GooglePay,
+            _ => PaymentMethodType::Card,
+        }
+    }
+    
     /// Extract card info from payment method
     fn extract_card_info(&self, payment_method: &StripePaymentMethod) -> PaymentMethodInfo {
         PaymentMethodInfo {
-            method_type: PaymentMethodType::Card,
+            method_type: Self::method_type(payment_method),
             last_four: payment_method.card.as_ref().map(|c| c.last4.clone()),
             card_brand: payment_method.card.as_ref().map(|c| c.brand.clone()),
             exp_month: payment_method.card.as_ref().map(|c| c.exp_month.to_string()),
@@ -299,6 +340,8 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
         &self,
         request: InitiatePaymentRequest,
     ) -> Result<InitiatePaymentResponse> {
+        let options = [sca_params(&request), capture_params(&request)].concat();
+        
         // Extract card data from payment method data
         let card_data = match &request.payment_method_data {
             PaymentMethodData::Card { number, exp_month, exp_year, cvc, name } => CardData {
@@ -317,6 +360,21 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
                     &request.customer_email,
                     &request.description,
                     request.metadata,
+                    options,
+                ).await?;
+                
+                return self.handle_intent_response(intent);
+            }
+            PaymentMethodData::DigitalWallet { wallet_type, token } => {
+                let payment_method_id = self.create_wallet_payment_method(*wallet_type, token).await?;
+                let intent = self.create_payment_intent(
+                    request.amount,
+                    &request.currency,
+                    &payment_method_id,
+                    &request.customer_email,
+                    &request.description,
+                    request.metadata,
+                    options,
                 ).await?;
                 
                 return self.handle_intent_response(intent);
@@ -335,6 +393,7 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
             &request.customer_email,
             &request.description,
             request.metadata,
+            options,
         ).await?;
         
         // Step 3: Handle the response based on status
@@ -349,7 +408,7 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
         let intent = self.confirm_payment_intent(&request.payment_id).await?;
         
         match intent.status.as_str() {
-            "succeeded" => {
+            "succeeded" | "requires_capture" => {
                 // Get payment method info if available
                 let payment_method = if let Some(ref pm) = intent.payment_method {
                     match self.get_payment_method(&pm.id).await {
@@ -386,21 +445,18 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
                 Ok(CompletePaymentActionResponse::Success {
                     payment_id,
                     transaction_id,
-                    payment_status: PaymentStatus::Succeeded,
+                    payment_status: Self::map_status(&intent.status),
                     payment_method,
                     receipt_url,
                 })
             }
             "requires_action" | "requires_source_action" => {
                 // Get the next action (3DS)
-                if let Some(action) = intent.next_action {
+                if let Some(challenge) = challenge(&intent) {
                     Ok(CompletePaymentActionResponse::RequiresAction {
                         payment_id: intent.id.clone(),
                         action_type: PaymentActionType::ThreeDSecure,
-                        action_data: json!({
-                            "type": action.type_,
-                            "redirect_url": action.redirect_to_url,
-                        }),
+                        action_data: challenge.action_data(),
                     })
                 } else {
                     Err(crate::Error::payment_error("Payment requires action but no action data"))
@@ -498,18 +554,25 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
         payload: &[u8],
         headers: &[(String, String)],
     ) -> Result<WebhookEvent> {
-        // Verify webhook signature
-        let _signature = headers.iter()
-            .find(|(k, _)| k == "Stripe-Signature")
+        // Verify webhook signature when a signing secret is configured
+        let signature = headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Stripe-Signature"))
             .map(|(_, v)| v.as_str())
             .unwrap_or("");
-        
-        // TODO: Implement proper signature verification
-        // For now, just parse the event
+        if !self.webhook_secret.is_empty() {
+            verify_signature(&self.webhook_secret, payload, signature, chrono::Utc::now().timestamp())?;
+        }
         
         let event: StripeWebhookEvent = serde_json::from_slice(payload)
             .map_err(|e| crate::Error::validation(format!("Invalid webhook payload: {}", e)))?;
         
+        // Checkout events mark orders paid, so they are only accepted signed
+        if event.type_.starts_with("checkout.session.") && self.webhook_secret.is_empty() {
+            return Err(crate::Error::validation(
+                "Checkout webhooks need a Stripe webhook secret (payment.stripe.webhook_secret)",
+            ));
+        }
+        
         let (event_type, payment_id, transaction_id) = match event.type_.as_str() {
             "payment_intent.succeeded" => (
                 WebhookEventType::PaymentSucceeded,
@@ -540,6 +603,31 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
                     .to_string(),
                 event.data.object.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()),
             ),
+            "checkout.session.completed" => (
+                // Delayed methods (e.g. bank debits) complete unpaid and settle later
+                if event.data.object.get("payment_status").and_then(|v| v.as_str()) == Some("unpaid") {
+                    WebhookEventType::PaymentProcessing
+                } else {
+                    WebhookEventType::CheckoutCompleted
+                },
+                event.data.object.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                event.data.object.get("payment_intent").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            ),
+            "checkout.session.async_payment_succeeded" => (
+                WebhookEventType::CheckoutCompleted,
+                event.data.object.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                event.data.object.get("payment_intent").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            ),
+            "checkout.session.async_payment_failed" => (
+                WebhookEventType::CheckoutFailed,
+                event.data.object.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                event.data.object.get("payment_intent").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            ),
+            "checkout.session.expired" => (
+                WebhookEventType::CheckoutExpired,
+                event.data.object.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                None,
+            ),
             _ => return Err(crate::Error::validation("Unsupported webhook event type")),
         };
         
@@ -613,10 +701,266 @@ impl AgnosticPaymentGateway for StripeAgnosticGateway {
         
         Ok(())
     }
+    
+    async fn create_hosted_checkout(&self, request: HostedCheckoutRequest) -> Result<HostedCheckoutPage> {
+        if self.webhook_secret.is_empty() {
+            return Err(crate::Error::validation(
+                "Stripe hosted checkout needs a webhook secret (payment.stripe.webhook_secret) to confirm payments",
+            ));
+        }
+        
+        let amount_in_cents = to_cents(request.amount)?;
+        let currency = request.currency.to_lowercase();
+        let order_id = request.order_id.to_string();
+        
+        match request.mode {
+            HostedCheckoutMode::CheckoutSession => {
+                let mut params: Vec<(&str, String)> = vec![
+                    ("mode", "payment".to_string()),
+                    ("line_items[0][quantity]", "1".to_string()),
+                    ("line_items[0][price_data][currency]", currency),
+                    ("line_items[0][price_data][unit_amount]", amount_in_cents.to_string()),
+                    ("line_items[0][price_data][product_data][name]", request.description),
+                    ("success_url", request.success_url),
+                    ("client_reference_id", order_id.clone()),
+                    ("metadata[order_id]", order_id.clone()),
+                    ("payment_intent_data[metadata][order_id]", order_id),
+                ];
+                if let Some(cancel_url) = request.cancel_url {
+                    params.push(("cancel_url", cancel_url));
+                }
+                if let Some(email) = request.customer_email {
+                    params.push(("customer_email", email));
+                }
+                if let Some(expires_at) = request.expires_at {
+                    params.push(("expires_at", expires_at.timestamp().to_string()));
+                }
+                
+                let session: StripeCheckoutSession = self
+                    .post_form("https://api.stripe.com/v1/checkout/sessions", &params)
+                    .await?;
+                let url = session.url
+                    .ok_or_else(|| crate::Error::payment_error("Stripe returned a checkout session without a URL"))?;
+                
+                Ok(HostedCheckoutPage {
+                    provider_id: session.id,
+                    url,
+                    expires_at: session.expires_at.and_then(|t| chrono::DateTime::from_timestamp(t, 0)),
+                })
+            }
+            HostedCheckoutMode::PaymentLink => {
+                // Payment links take a price rather than inline price data
+                let price: StripeObject = self
+                    .post_form(
+                        "https://api.stripe.com/v1/prices",
+                        &[
+                            ("currency", currency),
+                            ("unit_amount", amount_in_cents.to_string()),
+                            ("product_data[name]", request.description),
+                        ],
+                    )
+                    .await?;
+                
+                let params: Vec<(&str, String)> = vec![
+                    ("line_items[0][price]", price.id),
+                    ("line_items[0][quantity]", "1".to_string()),
+                    ("after_completion[type]", "redirect".to_string()),
+                    ("after_completion[redirect][url]", request.success_url),
+                    ("metadata[order_id]", order_id.clone()),
+                    ("payment_intent_data[metadata][order_id]", order_id),
+                    // The link pays one order, once
+                    ("restrictions[completed_sessions][limit]", "1".to_string()),
+                ];
+                let link: StripePaymentLink = self
+                    .post_form("https://api.stripe.com/v1/payment_links", &params)
+                    .await?;
+                
+                Ok(HostedCheckoutPage {
+                    provider_id: link.id,
+                    url: link.url,
+                    expires_at: None,
+                })
+            }
+        }
+    }
+    
+    async fn expire_hosted_checkout(&self, mode: HostedCheckoutMode, provider_id: &str) -> Result<()> {
+        let _: StripeObject = match mode {
+            HostedCheckoutMode::CheckoutSession => {
+                self.post_form(
+                    &format!("https://api.stripe.com/v1/checkout/sessions/{}/expire", provider_id),
+                    &[],
+                )
+                .await?
+            }
+            HostedCheckoutMode::PaymentLink => {
+                self.post_form(
+                    &format!("https://api.stripe.com/v1/payment_links/{}", provider_id),
+                    &[("active", "false".to_string())],
+                )
+                .await?
+            }
+        };
+        Ok(())
+    }
+    
+    fn hosted_checkout_event(&self, event: &WebhookEvent) -> Option<HostedCheckoutEvent> {
+        if !matches!(
+            event.event_type,
+            WebhookEventType::CheckoutCompleted | WebhookEventType::CheckoutFailed | WebhookEventType::CheckoutExpired
+        ) {
+            return None;
+        }
+        
+        Some(HostedCheckoutEvent {
+            checkout_id: event.payment_id.clone(),
+            link_id: event.data.get("payment_link").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            payment_id: event.transaction_id.clone(),
+            amount: event.data.get("amount_total").and_then(|v| v.as_i64()).map(|a| Decimal::from(a) / dec!(100)),
+            currency: event.data.get("currency").and_then(|v| v.as_str()).map(|s| s.to_uppercase()),
+        })
+    }
+    
+    fn accepts_encrypted_wallet_tokens(&self, _wallet: WalletType) -> bool {
+        true
+    }
+    
+    async fn register_apple_pay_domain(&self, domain: &str) -> Result<()> {
+        let _: StripeObject = self
+            .post_form(
+                "https://api.stripe.com/v1/payment_method_domains",
+                &[("domain_name", domain.to_string())],
+            )
+            .await?;
+        Ok(())
+    }
+    
+    async fn capture_payment(&self, payment_id: &str, amount: Decimal, final_capture: bool) -> Result<CaptureResponse> {
+        let amount_in_cents: i64 = (amount * dec!(100)).try_into().unwrap_or(0i64);
+        let intent: StripePaymentIntent = self
+            .post_form(
+                &format!("https://api.stripe.com/v1/payment_intents/{}/capture", payment_id),
+                &[
+                    ("amount_to_capture", amount_in_cents.to_string()),
+                    ("final_capture", final_capture.to_string()),
+                ],
+            )
+            .await?;
+        
+        Ok(CaptureResponse {
+            capture_id: intent.charges.data.first()
+                .map(|c| c.id.clone())
+                .unwrap_or_else(|| intent.id.clone()),
+            amount,
+            status: Self::map_status(&intent.status),
+        })
+    }
+    
+    async fn void_payment(&self, payment_id: &str) -> Result<()> {
+        let _: StripePaymentIntent = self
+            .post_form(
+                &format!("https://api.stripe.com/v1/payment_intents/{}/cancel", payment_id),
+                &[("cancellation_reason", "abandoned".to_string())],
+            )
+            .await?;
+        Ok(())
+    }
+    
+    async fn list_payouts(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<GatewayPayout>> {
+        let payouts: Vec<StripePayout> = self
+            .get_list(
+                "https://api.stripe.com/v1/payouts",
+                &[("created[gte]", since.timestamp().to_string())],
+                |payout: &StripePayout| payout.id.clone(),
+            )
+            .await?;
+        
+        let mut listed = Vec::with_capacity(payouts.len());
+        for payout in payouts {
+            let transactions: Vec<StripeBalanceTransaction> = self
+                .get_list(
+                    "https://api.stripe.com/v1/balance_transactions",
+                    &[("payout", payout.id.clone()), ("expand[]", "data.source".to_string())],
+                    |transaction: &StripeBalanceTransaction| transaction.id.clone(),
+                )
+                .await?;
+            listed.push(GatewayPayout {
+                payout_id: payout.id,
+                amount: from_cents(payout.amount),
+                currency: payout.currency.to_uppercase(),
+                status: payout.status,
+                arrival_date: chrono::DateTime::from_timestamp(payout.arrival_date, 0),
+                created_at: chrono::DateTime::from_timestamp(payout.created, 0).unwrap_or_else(chrono::Utc::now),
+                transactions: transactions.iter().filter_map(settlement_transaction).collect(),
+            });
+        }
+        Ok(listed)
+    }
 }
 
 // Helper methods
 impl StripeAgnosticGateway {
+    /// POST a form to the Stripe API
+    async fn post_form<T: DeserializeOwned>(&self, url: &str, params: &[(&str, String)]) -> Result<T> {
+        let response = self.client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .form(params)
+            .send()
+            .await
+            .map_err(|e| crate::Error::network(format!("Stripe API error: {}", e)))?;
+        
+        if !response.status().is_success() {
+            let error: StripeError = response.json().await
+                .map_err(|_| crate::Error::payment_error("Failed to parse Stripe error"))?;
+            return Err(crate::Error::payment_error(format!(
+                "Stripe error: {} - {}", 
+                error.error.code, 
+                error.error.message
+            )));
+        }
+        
+        response.json().await
+            .map_err(|e| crate::Error::payment_error(format!("Failed to parse response: {}", e)))
+    }
+    
+    /// GET every page of a Stripe list; `id` gives the cursor of an item
+    async fn get_list<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        params: &[(&str, String)],
+        id: impl Fn(&T) -> String,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut starting_after: Option<String> = None;
+        loop {
+            let mut query: Vec<(&str, String)> = params.to_vec();
+            query.push(("limit", "100".to_string()));
+            if let Some(cursor) = &starting_after {
+                query.push(("starting_after", cursor.clone()));
+            }
+            let response = self.client
+                .get(url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .query(&query)
+                .send()
+                .await
+                .map_err(|e| crate::Error::network(format!("Stripe API error: {}", e)))?;
+            
+            if !response.status().is_success() {
+                return Err(crate::Error::payment_error(format!("Failed to list {}", url)));
+            }
+            
+            let page: StripeList<T> = response.json().await
+                .map_err(|e| crate::Error::payment_error(format!("Failed to parse response: {}", e)))?;
+            starting_after = page.data.last().map(&id);
+            items.extend(page.data);
+            if !page.has_more || starting_after.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+    
     async fn create_payment_intent_with_token(
         &self,
         amount: Decimal,
@@ -625,6 +969,7 @@ impl StripeAgnosticGateway {
         customer_email: &str,
         description: &str,
         metadata: serde_json::Value,
+        options: Vec<(&'static str, String)>,
     ) -> Result<StripePaymentIntent> {
         let amount_in_cents: i64 = (amount * dec!(100)).try_into().unwrap_or(0i64);
         
@@ -633,7 +978,6 @@ impl StripeAgnosticGateway {
             ("currency", currency.to_lowercase()),
             ("payment_method", token.to_string()),
             ("confirmation_method", "manual".to_string()),
-            ("capture_method", "automatic".to_string()),
             ("receipt_email", customer_email.to_string()),
             ("description", description.to_string()),
             ("confirm", "true".to_string()),
@@ -642,6 +986,7 @@ impl StripeAgnosticGateway {
         if let Some(order_id) = metadata.get("order_id").and_then(|v| v.as_str()) {
             params.push(("metadata[order_id]", order_id.to_string()));
         }
+        params.extend(options);
         
         let response = self.client
             .post("https://api.stripe.com/v1/payment_intents")
@@ -687,11 +1032,11 @@ impl StripeAgnosticGateway {
     
     fn handle_intent_response(&self, intent: StripePaymentIntent) -> Result<InitiatePaymentResponse> {
         match intent.status.as_str() {
-            "succeeded" => {
+            "succeeded" | "requires_capture" => {
                 let payment_method = intent.payment_method
                     .as_ref()
                     .map(|pm| PaymentMethodInfo {
-                        method_type: PaymentMethodType::Card,
+                        method_type: Self::method_type(pm),
                         last_four: pm.card.as_ref().map(|c| c.last4.clone()),
                         card_brand: pm.card.as_ref().map(|c| c.brand.clone()),
                         exp_month: pm.card.as_ref().map(|c| c.exp_month.to_string()),
@@ -719,22 +1064,18 @@ impl StripeAgnosticGateway {
                 Ok(InitiatePaymentResponse::Success {
                     payment_id,
                     transaction_id,
-                    payment_status: PaymentStatus::Succeeded,
+                    payment_status: Self::map_status(&intent.status),
                     payment_method,
                     receipt_url,
                 })
             }
             "requires_action" | "requires_source_action" => {
                 // 3D Secure required
-                if let Some(action) = intent.next_action {
+                if let Some(challenge) = challenge(&intent) {
                     Ok(InitiatePaymentResponse::RequiresAction {
                         payment_id: intent.id.clone(),
                         action_type: PaymentActionType::ThreeDSecure,
-                        action_data: json!({
-                            "type": action.type_,
-                            "redirect_url": action.redirect_to_url,
-                            "use_stripe_sdk": action.use_stripe_sdk,
-                        }),
+                        action_data: challenge.action_data(),
                         expires_at: chrono::Utc::now() + chrono::Duration::minutes(30),
                     })
                 } else {
@@ -762,6 +1103,78 @@ impl StripeAgnosticGateway {
     }
 }
 
+/// Payment intent parameters for Strong Customer Authentication: where
+/// Stripe returns the customer after a challenge, and whether fraud
+/// screening asked for a challenge
+fn sca_params(request: &InitiatePaymentRequest) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    if let Some(return_url) = &request.return_url {
+        params.push(("return_url", return_url.clone()));
+    }
+    if request.metadata.get("three_d_secure").and_then(|v| v.as_str()) == Some("required") {
+        params.push(("payment_method_options[card][request_three_d_secure]", "any".to_string()));
+    }
+    params
+}
+
+/// Intent options for the capture method; manual captures may be split
+/// over several captures where the card supports it
+fn capture_params(request: &InitiatePaymentRequest) -> Vec<(&'static str, String)> {
+    match request.capture_method {
+        CaptureMethod::Automatic => vec![("capture_method", "automatic".to_string())],
+        CaptureMethod::Manual => vec![
+            ("capture_method", "manual".to_string()),
+            ("payment_method_options[card][request_multicapture]", "if_available".to_string()),
+        ],
+    }
+}
+
+/// A payout's balance transaction; None for the payout itself
+fn settlement_transaction(transaction: &StripeBalanceTransaction) -> Option<SettlementTransaction> {
+    let kind = match transaction.type_.as_str() {
+        "payout" => return None,
+        "charge" | "payment" => SettlementKind::Charge,
+        "refund" | "payment_refund" => SettlementKind::Refund,
+        "stripe_fee" | "stripe_fx_fee" | "application_fee" | "tax" => SettlementKind::Fee,
+        _ => SettlementKind::Adjustment,
+    };
+    // Charges are recorded by their payment intent, refunds by their own ID
+    let reference = match &transaction.source {
+        Some(serde_json::Value::String(id)) => Some(id.clone()),
+        Some(source) => source
+            .get("payment_intent")
+            .filter(|id| kind == SettlementKind::Charge && id.is_string())
+            .or_else(|| source.get("id"))
+            .and_then(|id| id.as_str())
+            .map(str::to_string),
+        None => None,
+    };
+    Some(SettlementTransaction {
+        transaction_id: transaction.id.clone(),
+        kind,
+        reference,
+        amount: from_cents(transaction.amount),
+        fee: from_cents(transaction.fee),
+        net: from_cents(transaction.net),
+        currency: transaction.currency.to_uppercase(),
+        description: transaction.description.clone(),
+    })
+}
+
+/// The 3-D Secure challenge of an intent that requires action
+fn challenge(intent: &StripePaymentIntent) -> Option<ThreeDsChallenge> {
+    let action = intent.next_action.as_ref()?;
+    match action.type_.as_str() {
+        "redirect_to_url" => action
+            .redirect_to_url
+            .as_ref()
+            .and_then(|redirect| redirect.get("url"))
+            .and_then(|url| url.as_str())
+            .map(ThreeDsChallenge::redirect),
+        _ => intent.client_secret.as_deref().map(ThreeDsChallenge::sdk),
+    }
+}
+
 // Stripe API types
 #[derive(Debug, Deserialize)]
 struct StripeError {
@@ -770,6 +1183,8 @@ struct StripeError {
 
 #[derive(Debug, Deserialize)]
 struct StripeErrorDetails {
+    // Missing on some errors, e.g. invalid API keys
+    #[serde(default)]
     code: String,
     message: String,
 }
@@ -791,6 +1206,14 @@ struct StripeCard {
     last4: String,
     exp_month: u32,
     exp_year: u32,
+    #[serde(default)]
+    wallet: Option<StripeCardWallet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeCardWallet {
+    #[serde(rename = "type")]
+    type_: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -808,6 +1231,7 @@ struct StripePaymentIntent {
     #[allow(dead_code)]
     currency: String,
     charges: StripeList<StripeCharge>,
+    client_secret: Option<String>,
     next_action: Option<StripeNextAction>,
     payment_method: Option<StripePaymentMethod>,
 }
@@ -823,7 +1247,6 @@ struct StripeNextAction {
     #[serde(rename = "type")]
     type_: String,
     redirect_to_url: Option<serde_json::Value>,
-    use_stripe_sdk: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -838,6 +1261,32 @@ struct StripeRefund {
 #[derive(Debug, Deserialize)]
 struct StripeList<T> {
     data: Vec<T>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripePayout {
+    id: String,
+    amount: i64,
+    currency: String,
+    status: String,
+    arrival_date: i64,
+    created: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeBalanceTransaction {
+    id: String,
+    #[serde(rename = "type")]
+    type_: String,
+    amount: i64,
+    fee: i64,
+    net: i64,
+    currency: String,
+    description: Option<String>,
+    /// The charge or refund, expanded, or its ID
+    source: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -855,6 +1304,25 @@ struct StripeWebhookData {
     object: serde_json::Value,
 }
 
+#[derive(Debug, Deserialize)]
+struct StripeCheckoutSession {
+    id: String,
+    url: Option<String>,
+    expires_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripePaymentLink {
+    id: String,
+    url: String,
+}
+
+/// Any Stripe object, when only its ID is needed
+#[derive(Debug, Deserialize)]
+struct StripeObject {
+    id: String,
+}
+
 // Helper types
 struct CardData {
     number: String,
@@ -864,6 +1332,68 @@ struct CardData {
     name: String,
 }
 
+/// What a wallet token becomes at Stripe
+#[derive(Debug, PartialEq)]
+enum StripeWalletToken {
+    /// Payment method from Stripe.js (`pm_...`)
+    PaymentMethod(String),
+    /// Card token (`tok_...`), e.g. from Google Pay with Stripe as the
+    /// tokenization gateway
+    CardToken(String),
+    /// `/v1/tokens` parameters for an Apple Pay `PKPaymentToken`
+    ApplePay(Vec<(&'static str, String)>),
+}
+
+fn parse_wallet_token(wallet: WalletType, token: &str) -> Result<StripeWalletToken> {
+    let token = token.trim();
+    if token.starts_with("pm_") {
+        return Ok(StripeWalletToken::PaymentMethod(token.to_string()));
+    }
+    if token.starts_with("tok_") {
+        return Ok(StripeWalletToken::CardToken(token.to_string()));
+    }
+    
+    let value: serde_json::Value = serde_json::from_str(token)
+        .map_err(|_| crate::Error::validation("Invalid wallet token"))?;
+    match wallet {
+        WalletType::GooglePay => value
+            .get("id")
+            .and_then(|v| v.as_str())
+            .filter(|id| id.starts_with("tok_"))
+            .map(|id| StripeWalletToken::CardToken(id.to_string()))
+            .ok_or_else(|| {
+                crate::Error::validation("Google Pay tokens for Stripe need Stripe as the tokenization gateway")
+            }),
+        WalletType::ApplePay => {
+            // A full PKPaymentToken, or just its paymentData
+            let (payment_data, payment_method, transaction_id) = match value.get("paymentData") {
+                Some(payment_data) => (
+                    payment_data,
+                    value.get("paymentMethod"),
+                    value.get("transactionIdentifier").and_then(|v| v.as_str()),
+                ),
+                None => (&value, None, None),
+            };
+            if payment_data.get("data").is_none() || payment_data.get("header").is_none() {
+                return Err(crate::Error::validation("Invalid Apple Pay payment token"));
+            }
+            
+            let mut params = vec![("pk_token", payment_data.to_string())];
+            let method_field = |name: &str| payment_method.and_then(|m| m.get(name)).and_then(|v| v.as_str());
+            if let Some(name) = method_field("displayName") {
+                params.push(("pk_token_instrument_name", name.to_string()));
+            }
+            if let Some(network) = method_field("network") {
+                params.push(("pk_token_payment_network", network.to_string()));
+            }
+            if let Some(transaction_id) = transaction_id {
+                params.push(("pk_token_transaction_id", transaction_id.to_string()));
+            }
+            Ok(StripeWalletToken::ApplePay(params))
+        }
+    }
+}
+
 fn map_refund_reason(reason: &str) -> String {
     match reason {
         "duplicate" => "duplicate".to_string(),
@@ -872,3 +1402,273 @@ fn map_refund_reason(reason: &str) -> String {
         _ => "requested_by_customer".to_string(),
     }
 }
+
+/// Amount from the smallest currency unit
+fn from_cents(cents: i64) -> Decimal {
+    Decimal::new(cents, 2)
+}
+
+/// Amount in the smallest currency unit
+fn to_cents(amount: Decimal) -> Result<i64> {
+    (amount * dec!(100))
+        .round()
+        .to_i64()
+        .ok_or_else(|| crate::Error::validation("Invalid amount"))
+}
+
+/// Check a `Stripe-Signature` header (`t=<timestamp>,v1=<hmac>,...`): an
+/// HMAC-SHA256 of `<timestamp>.<payload>` under the endpoint's signing secret
+fn verify_signature(secret: &str, payload: &[u8], header: &str, now: i64) -> Result<()> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    
+    let timestamp = timestamp.ok_or_else(|| crate::Error::validation("Missing Stripe webhook signature"))?;
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        return Err(crate::Error::validation("Stripe webhook signature timestamp is too old"));
+    }
+    
+    let verified = signatures.iter().any(|signature| {
+        let Ok(expected) = hex::decode(signature) else {
+            return false;
+        };
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+            return false;
+        };
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(payload);
+        mac.verify_slice(&expected).is_ok()
+    });
+    if !verified {
+        return Err(crate::Error::validation("Invalid webhook signature"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sign(secret: &str, payload: &[u8], timestamp: i64) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(payload);
+        format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let payload = br#"{"id":"evt_1"}"#;
+        let header = sign("whsec_test", payload, 1_700_000_000);
+
+        assert!(verify_signature("whsec_test", payload, &header, 1_700_000_100).is_ok());
+        // Secret rotation sends one signature per secret
+        let rotated = format!("{},v1=00ff", header);
+        assert!(verify_signature("whsec_test", payload, &rotated, 1_700_000_000).is_ok());
+
+        assert!(verify_signature("whsec_other", payload, &header, 1_700_000_000).is_err());
+        assert!(verify_signature("whsec_test", br#"{"id":"evt_2"}"#, &header, 1_700_000_000).is_err());
+        assert!(verify_signature("whsec_test", payload, &header, 1_700_000_301).is_err());
+        assert!(verify_signature("whsec_test", payload, "v1=abc", 1_700_000_000).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkout_webhook() {
+        let gateway = StripeAgnosticGateway::new("sk_test".to_string(), "whsec_test".to_string());
+        let payload = serde_json::to_vec(&json!({
+            "id": "evt_1",
+            "type": "checkout.session.completed",
+            "data": {"object": {
+                "id": "cs_test_1",
+                "payment_status": "paid",
+                "payment_intent": "pi_1",
+                "payment_link": "plink_1",
+                "amount_total": 12550,
+                "currency": "eur",
+            }},
+        }))
+        .unwrap();
+        let headers = vec![(
+            "stripe-signature".to_string(),
+            sign("whsec_test", &payload, chrono::Utc::now().timestamp()),
+        )];
+
+        let event = gateway.handle_webhook(&payload, &headers).await.unwrap();
+        assert_eq!(event.event_type, WebhookEventType::CheckoutCompleted);
+        assert_eq!(
+            gateway.hosted_checkout_event(&event),
+            Some(HostedCheckoutEvent {
+                checkout_id: "cs_test_1".to_string(),
+                link_id: Some("plink_1".to_string()),
+                payment_id: Some("pi_1".to_string()),
+                amount: Some(dec!(125.50)),
+                currency: Some("EUR".to_string()),
+            })
+        );
+
+        assert!(gateway.handle_webhook(&payload, &[]).await.is_err());
+        let unsigned = StripeAgnosticGateway::new("sk_test".to_string(), String::new());
+        assert!(unsigned.handle_webhook(&payload, &[]).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_wallet_token() {
+        assert_eq!(
+            parse_wallet_token(WalletType::ApplePay, "pm_123").unwrap(),
+            StripeWalletToken::PaymentMethod("pm_123".to_string())
+        );
+        assert_eq!(
+            parse_wallet_token(WalletType::GooglePay, r#"{"id":"tok_abc","object":"token"}"#).unwrap(),
+            StripeWalletToken::CardToken("tok_abc".to_string())
+        );
+        // Google Pay tokens for another gateway can't be used
+        assert!(parse_wallet_token(WalletType::GooglePay, r#"{"signature":"x","protocolVersion":"ECv2"}"#).is_err());
+
+        let pk_token = json!({
+            "paymentData": {"version": "EC_v1", "data": "abc", "signature": "sig", "header": {"transactionId": "t1"}},
+            "paymentMethod": {"displayName": "Visa 1234", "network": "Visa", "type": "debit"},
+            "transactionIdentifier": "t1",
+        });
+        let StripeWalletToken::ApplePay(params) =
+            parse_wallet_token(WalletType::ApplePay, &pk_token.to_string()).unwrap()
+        else {
+            panic!("expected Apple Pay token parameters");
+        };
+        let pk: serde_json::Value = serde_json::from_str(&params[0].1).unwrap();
+        assert_eq!(params[0].0, "pk_token");
+        assert_eq!(pk, pk_token["paymentData"]);
+        assert!(params.contains(&("pk_token_instrument_name", "Visa 1234".to_string())));
+        assert!(params.contains(&("pk_token_payment_network", "Visa".to_string())));
+        assert!(params.contains(&("pk_token_transaction_id", "t1".to_string())));
+
+        // Bare paymentData works too
+        assert!(matches!(
+            parse_wallet_token(WalletType::ApplePay, &pk_token["paymentData"].to_string()).unwrap(),
+            StripeWalletToken::ApplePay(_)
+        ));
+        assert!(parse_wallet_token(WalletType::ApplePay, "{}").is_err());
+        assert!(parse_wallet_token(WalletType::ApplePay, "not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hosted_checkout_needs_webhook_secret() {
+        let gateway = StripeAgnosticGateway::new("sk_test".to_string(), String::new());
+        let request = HostedCheckoutRequest {
+            mode: HostedCheckoutMode::CheckoutSession,
+            order_id: uuid::Uuid::new_v4(),
+            amount: dec!(10),
+            currency: "USD".to_string(),
+            customer_email: None,
+            description: "Order #1001".to_string(),
+            success_url: "https://shop.example.com/thanks".to_string(),
+            cancel_url: None,
+            expires_at: None,
+        };
+        assert!(gateway.create_hosted_checkout(request).await.is_err());
+    }
+
+    #[test]
+    fn test_three_ds_challenge() {
+        let intent: StripePaymentIntent = serde_json::from_value(json!({
+            "id": "pi_1",
+            "status": "requires_action",
+            "amount": 2500,
+            "currency": "eur",
+            "charges": {"data": []},
+            "client_secret": "pi_1_secret",
+            "next_action": {"type": "redirect_to_url", "redirect_to_url": {"url": "https://hooks.stripe.com/3d_secure/abc"}},
+        }))
+        .unwrap();
+        assert_eq!(challenge(&intent), Some(ThreeDsChallenge::redirect("https://hooks.stripe.com/3d_secure/abc")));
+
+        let intent: StripePaymentIntent = serde_json::from_value(json!({
+            "id": "pi_1",
+            "status": "requires_action",
+            "amount": 2500,
+            "currency": "eur",
+            "charges": {"data": []},
+            "client_secret": "pi_1_secret",
+            "next_action": {"type": "use_stripe_sdk"},
+        }))
+        .unwrap();
+        assert_eq!(challenge(&intent), Some(ThreeDsChallenge::sdk("pi_1_secret")));
+    }
+
+    #[test]
+    fn test_manual_capture() {
+        let mut request: InitiatePaymentRequest = serde_json::from_value(json!({
+            "amount": "25.00",
+            "currency": "EUR",
+            "payment_method_type": "card",
+            "order_id": "00000000-0000-0000-0000-000000000001",
+            "customer_id": null,
+            "customer_email": "jane@example.com",
+            "customer_ip": null,
+            "billing_address": null,
+            "shipping_address": null,
+            "payment_method_data": {"type": "card_token", "token": "pm_1"},
+            "save_payment_method": false,
+            "description": "Order #1001",
+            "metadata": {},
+        }))
+        .unwrap();
+        assert_eq!(capture_params(&request), vec![("capture_method", "automatic".to_string())]);
+
+        request.capture_method = CaptureMethod::Manual;
+        let params = capture_params(&request);
+        assert!(params.contains(&("capture_method", "manual".to_string())));
+        assert_eq!(params.len(), 2);
+        assert_eq!(StripeAgnosticGateway::map_status("requires_capture"), PaymentStatus::Authorized);
+    }
+
+    #[test]
+    fn test_settlement_transaction() {
+        let charge: StripeBalanceTransaction = serde_json::from_value(json!({
+            "id": "txn_1",
+            "type": "charge",
+            "amount": 2500,
+            "fee": 103,
+            "net": 2397,
+            "currency": "eur",
+            "description": null,
+            "source": {"id": "ch_1", "object": "charge", "payment_intent": "pi_1"},
+        }))
+        .unwrap();
+        let line = settlement_transaction(&charge).unwrap();
+        assert_eq!(line.kind, SettlementKind::Charge);
+        assert_eq!(line.reference.as_deref(), Some("pi_1"));
+        assert_eq!((line.amount, line.fee, line.net), (dec!(25.00), dec!(1.03), dec!(23.97)));
+        assert_eq!(line.currency, "EUR");
+
+        let refund: StripeBalanceTransaction = serde_json::from_value(json!({
+            "id": "txn_2",
+            "type": "refund",
+            "amount": -1000,
+            "fee": 0,
+            "net": -1000,
+            "currency": "eur",
+            "description": "REFUND FOR CHARGE",
+            "source": {"id": "re_1", "object": "refund", "payment_intent": "pi_1"},
+        }))
+        .unwrap();
+        let line = settlement_transaction(&refund).unwrap();
+        assert_eq!(line.kind, SettlementKind::Refund);
+        assert_eq!(line.reference.as_deref(), Some("re_1"));
+        assert_eq!(line.amount, dec!(-10.00));
+
+        let payout: StripeBalanceTransaction = serde_json::from_value(json!({
+            "id": "txn_3", "type": "payout", "amount": -1397, "fee": 0, "net": -1397,
+            "currency": "eur", "description": null, "source": "po_1",
+        }))
+        .unwrap();
+        assert!(settlement_transaction(&payout).is_none());
+    }
+}
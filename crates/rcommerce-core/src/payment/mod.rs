@@ -1,6 +1,11 @@
 pub mod agnostic;
 pub mod gateways;
 pub mod dunning;
+pub mod tender;
+pub mod wallet;
+
+pub use tender::{GiftCardTender, TenderPlan};
+pub use wallet::{DecryptedWalletToken, WalletTokenDecryptor, WalletType};
 
 #[cfg(test)]
 mod tests;
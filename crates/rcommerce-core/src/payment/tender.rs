@@ -0,0 +1,138 @@
+//! Split tender
+//!
+//! An order can be paid with gift cards and a gateway payment method
+//! (card, wallet, ...) together. Gift cards are applied first, in the order
+//! the customer entered them, each up to its balance; whatever is left is
+//! charged through the gateway.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::GiftCard;
+use crate::{Error, Result};
+
+/// The part of an order paid with one gift card
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GiftCardTender {
+    pub gift_card_id: Uuid,
+    /// Masked code
+    pub code: String,
+    pub amount: Decimal,
+}
+
+/// How an order total is split between gift cards and the gateway
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TenderPlan {
+    pub gift_cards: Vec<GiftCardTender>,
+    /// Left to charge through the payment gateway
+    pub gateway_amount: Decimal,
+}
+
+impl TenderPlan {
+    /// Apply gift cards to `total`, checking each can pay in `currency`
+    pub fn new(total: Decimal, currency: &str, cards: &[GiftCard], now: DateTime<Utc>) -> Result<Self> {
+        let mut remaining = total;
+        let mut gift_cards: Vec<GiftCardTender> = Vec::new();
+
+        for card in cards {
+            let code = card.masked_code();
+            if gift_cards.iter().any(|tender| tender.gift_card_id == card.id) {
+                return Err(Error::validation(format!("Gift card {} is entered more than once", code)));
+            }
+            if let Some(reason) = card.unusable_reason(now) {
+                return Err(Error::validation(format!("Gift card {} {}", code, reason)));
+            }
+            if !card.currency.eq_ignore_ascii_case(currency) {
+                return Err(Error::validation(format!(
+                    "Gift card {} is in {} and cannot pay for an order in {}",
+                    code, card.currency, currency
+                )));
+            }
+            if remaining <= Decimal::ZERO {
+                return Err(Error::validation(format!("Gift card {} is not needed to pay for this order", code)));
+            }
+
+            let amount = card.balance.min(remaining);
+            remaining -= amount;
+            gift_cards.push(GiftCardTender {
+                gift_card_id: card.id,
+                code,
+                amount,
+            });
+        }
+
+        Ok(Self {
+            gift_cards,
+            gateway_amount: remaining.max(Decimal::ZERO),
+        })
+    }
+
+    /// Paid with gift cards
+    pub fn gift_card_total(&self) -> Decimal {
+        self.gift_cards.iter().map(|tender| tender.amount).sum()
+    }
+
+    /// Whether the gateway has anything to charge
+    pub fn needs_gateway(&self) -> bool {
+        self.gateway_amount > Decimal::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GiftCardStatus;
+    use rust_decimal_macros::dec;
+
+    fn card(balance: Decimal, currency: &str) -> GiftCard {
+        GiftCard {
+            id: Uuid::new_v4(),
+            code: GiftCard::generate_code(),
+            currency: currency.to_string(),
+            initial_balance: balance,
+            balance,
+            status: GiftCardStatus::Active,
+            expires_at: None,
+            recipient_email: None,
+            recipient_name: None,
+            message: None,
+            note: None,
+            issued_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_split_tender() {
+        let now = Utc::now();
+        let first = card(dec!(25), "USD");
+        let second = card(dec!(50), "USD");
+
+        let plan = TenderPlan::new(dec!(60), "USD", &[first.clone(), second.clone()], now).unwrap();
+        assert_eq!(plan.gift_cards[0].amount, dec!(25));
+        assert_eq!(plan.gift_cards[1].amount, dec!(35));
+        assert_eq!(plan.gift_card_total(), dec!(60));
+        assert!(!plan.needs_gateway());
+
+        let plan = TenderPlan::new(dec!(80), "USD", std::slice::from_ref(&first), now).unwrap();
+        assert_eq!(plan.gateway_amount, dec!(55));
+        assert!(plan.needs_gateway());
+
+        let plan = TenderPlan::new(dec!(80), "USD", &[], now).unwrap();
+        assert_eq!(plan.gateway_amount, dec!(80));
+    }
+
+    #[test]
+    fn test_split_tender_rejects_cards() {
+        let now = Utc::now();
+        let usd = card(dec!(100), "USD");
+
+        assert!(TenderPlan::new(dec!(10), "USD", &[usd.clone(), usd.clone()], now).is_err());
+        assert!(TenderPlan::new(dec!(10), "EUR", std::slice::from_ref(&usd), now).is_err());
+        assert!(TenderPlan::new(dec!(10), "USD", &[usd, card(dec!(5), "USD")], now).is_err());
+        assert!(TenderPlan::new(dec!(10), "USD", &[card(dec!(0), "USD")], now).is_err());
+    }
+}
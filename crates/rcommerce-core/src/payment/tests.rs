@@ -70,6 +70,108 @@ mod tests {
         assert!(!Succeeded.can_transition_to(Processing));
         assert!(!Failed.can_transition_to(Succeeded));
     }
+    
+    struct TestDecryptor;
+    
+    #[async_trait::async_trait]
+    impl crate::payment::WalletTokenDecryptor for TestDecryptor {
+        async fn decrypt(
+            &self,
+            wallet: crate::payment::WalletType,
+            _token: &str,
+        ) -> crate::Result<crate::payment::DecryptedWalletToken> {
+            Ok(crate::payment::DecryptedWalletToken {
+                wallet,
+                number: "4111111111111111".to_string(),
+                exp_month: "12".to_string(),
+                exp_year: "2030".to_string(),
+                cryptogram: Some("AgAAAAAAAIR8CQrXcIhbQAAAAAA=".to_string()),
+                eci: Some("05".to_string()),
+                card_network: Some("visa".to_string()),
+            })
+        }
+    }
+    
+    fn wallet_request(method: crate::payment::agnostic::PaymentMethodType) -> crate::payment::agnostic::InitiatePaymentRequest {
+        use crate::payment::agnostic::*;
+        InitiatePaymentRequest {
+            amount: dec!(25.00),
+            currency: "USD".to_string(),
+            payment_method_type: method,
+            order_id: uuid::Uuid::new_v4(),
+            customer_id: None,
+            customer_email: "buyer@example.com".to_string(),
+            customer_ip: None,
+            billing_address: None,
+            shipping_address: None,
+            payment_method_data: PaymentMethodData::DigitalWallet {
+                wallet_type: crate::payment::WalletType::ApplePay,
+                token: r#"{"version":"EC_v1","data":"abc","header":{}}"#.to_string(),
+            },
+            save_payment_method: false,
+            description: "Order #1001".to_string(),
+            metadata: serde_json::json!({}),
+            return_url: None,
+            capture_method: CaptureMethod::Automatic,
+        }
+    }
+    
+    #[test]
+    fn test_three_ds_challenge_from_action_data() {
+        use crate::payment::agnostic::{ChallengeDisplay, ThreeDsChallenge};
+        
+        let challenge = ThreeDsChallenge::redirect("https://acs.example.com/challenge");
+        assert_eq!(ThreeDsChallenge::from_action_data(&challenge.action_data()), Some(challenge.clone()));
+        
+        let stripe = serde_json::json!({"type": "redirect_to_url", "redirect_url": {"url": "https://acs.example.com/challenge"}});
+        assert_eq!(ThreeDsChallenge::from_action_data(&stripe), Some(challenge.clone()));
+        
+        let airwallex = serde_json::json!({
+            "client_secret": "secret",
+            "next_action": {"type": "redirect", "method": "POST", "url": "https://acs.example.com/challenge", "data": {"creq": "abc"}},
+        });
+        let posted = ThreeDsChallenge::from_action_data(&airwallex).unwrap();
+        assert_eq!(posted.form.get("creq").map(String::as_str), Some("abc"));
+        assert_eq!(posted.displayed_as(ChallengeDisplay::Iframe).display, ChallengeDisplay::Iframe);
+        
+        let sdk = ThreeDsChallenge::from_action_data(&serde_json::json!({"client_secret": "secret"})).unwrap();
+        assert_eq!(sdk.display, ChallengeDisplay::Sdk);
+        assert_eq!(sdk.displayed_as(ChallengeDisplay::Iframe).display, ChallengeDisplay::Sdk);
+        assert_eq!(ThreeDsChallenge::from_action_data(&serde_json::json!({"status": "PENDING"})), None);
+    }
+    
+    #[tokio::test]
+    async fn test_prepare_wallet_payment() {
+        use crate::payment::agnostic::{PaymentMethodData, PaymentMethodType, PaymentService};
+        use crate::payment::gateways::wechatpay_agnostic::WeChatPayAgnosticGateway;
+        
+        let wechat = WeChatPayAgnosticGateway::new(
+            "mch".to_string(),
+            "key".to_string(),
+            "app".to_string(),
+            "serial".to_string(),
+            "private".to_string(),
+            true,
+        );
+        let mock = MockPaymentGateway::new();
+        let mut service = PaymentService::new("mock".to_string());
+        
+        // The token must be for the selected method
+        assert!(service.prepare_payment(&mock, wallet_request(PaymentMethodType::GooglePay)).await.is_err());
+        
+        // Gateways that take encrypted tokens get them as is
+        let prepared = service.prepare_payment(&mock, wallet_request(PaymentMethodType::ApplePay)).await.unwrap();
+        assert!(matches!(prepared.payment_method_data, PaymentMethodData::DigitalWallet { .. }));
+        
+        // Others need a decryptor
+        assert!(service.prepare_payment(&wechat, wallet_request(PaymentMethodType::ApplePay)).await.is_err());
+        service.set_wallet_decryptor(std::sync::Arc::new(TestDecryptor));
+        let prepared = service.prepare_payment(&wechat, wallet_request(PaymentMethodType::ApplePay)).await.unwrap();
+        match prepared.payment_method_data {
+            PaymentMethodData::DecryptedWallet { token } => assert_eq!(token.eci.as_deref(), Some("05")),
+            other => panic!("expected a decrypted token, got {:?}", other),
+        }
+    }
 }
 
 impl crate::payment::PaymentStatus {
@@ -0,0 +1,194 @@
+//! Wallet payments (Apple Pay, Google Pay)
+//!
+//! The storefront collects an encrypted payment token from the wallet and
+//! sends it as `PaymentMethodData::DigitalWallet`. Stripe and Airwallex take
+//! the encrypted token as is; for gateways that need the card data, a
+//! `WalletTokenDecryptor` registered on the `PaymentService` decrypts it
+//! first (e.g. with the merchant's Apple Pay payment processing key in an HSM).
+//!
+//! Apple Pay on the web also needs the storefront's domain verified and a
+//! merchant session for each payment sheet, which `ApplePayMerchantValidator`
+//! requests from Apple with the merchant identity certificate.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ApplePayConfig;
+use crate::payment::agnostic::PaymentMethodType;
+use crate::{Error, Result};
+
+/// A wallet that hands out encrypted payment tokens
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletType {
+    ApplePay,
+    GooglePay,
+}
+
+impl WalletType {
+    pub fn method_type(&self) -> PaymentMethodType {
+        match self {
+            WalletType::ApplePay => PaymentMethodType::ApplePay,
+            WalletType::GooglePay => PaymentMethodType::GooglePay,
+        }
+    }
+}
+
+/// Card data from a decrypted wallet token: a device account number (DPAN)
+/// with the one-time cryptogram that authorizes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecryptedWalletToken {
+    pub wallet: WalletType,
+    /// Device account number
+    pub number: String,
+    pub exp_month: String,
+    pub exp_year: String,
+    /// Online payment cryptogram (3-D Secure tokens)
+    pub cryptogram: Option<String>,
+    /// Electronic commerce indicator
+    pub eci: Option<String>,
+    /// Card network, e.g. "visa"
+    pub card_network: Option<String>,
+}
+
+/// Decrypts wallet payment tokens for gateways that can't
+#[async_trait]
+pub trait WalletTokenDecryptor: Send + Sync {
+    async fn decrypt(&self, wallet: WalletType, token: &str) -> Result<DecryptedWalletToken>;
+}
+
+/// Requests Apple Pay merchant sessions with the merchant identity certificate
+pub struct ApplePayMerchantValidator {
+    client: reqwest::Client,
+    merchant_id: String,
+    display_name: String,
+    domains: Vec<String>,
+}
+
+impl ApplePayMerchantValidator {
+    /// None when Apple Pay is disabled or no merchant identity certificate is
+    /// configured (e.g. when the gateway's JS SDK validates the merchant)
+    pub fn from_config(config: &ApplePayConfig) -> Result<Option<Self>> {
+        let (Some(merchant_id), Some(cert_path), Some(key_path)) = (
+            config.merchant_id.as_ref(),
+            config.merchant_identity_cert.as_ref(),
+            config.merchant_identity_key.as_ref(),
+        ) else {
+            return Ok(None);
+        };
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let mut pem = std::fs::read(cert_path)
+            .map_err(|e| Error::Config(format!("Failed to read Apple Pay merchant identity certificate: {}", e)))?;
+        pem.push(b'\n');
+        pem.extend(
+            std::fs::read(key_path)
+                .map_err(|e| Error::Config(format!("Failed to read Apple Pay merchant identity key: {}", e)))?,
+        );
+        let identity = reqwest::Identity::from_pem(&pem)
+            .map_err(|e| Error::Config(format!("Invalid Apple Pay merchant identity: {}", e)))?;
+        let client = reqwest::Client::builder()
+            .identity(identity)
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to build Apple Pay client: {}", e)))?;
+
+        Ok(Some(Self {
+            client,
+            merchant_id: merchant_id.clone(),
+            display_name: config.display_name.clone(),
+            domains: config.domains.clone(),
+        }))
+    }
+
+    /// Merchant session for an Apple Pay sheet opened on `domain`, passed
+    /// back to the browser as is
+    pub async fn create_session(&self, validation_url: &str, domain: &str) -> Result<serde_json::Value> {
+        if !is_apple_pay_validation_url(validation_url) {
+            return Err(Error::validation("validation_url is not an Apple Pay server"));
+        }
+        if !self.domains.iter().any(|allowed| allowed.eq_ignore_ascii_case(domain)) {
+            return Err(Error::validation("Domain is not one of payment.wallets.apple_pay.domains"));
+        }
+
+        let response = self
+            .client
+            .post(validation_url)
+            .json(&serde_json::json!({
+                "merchantIdentifier": self.merchant_id,
+                "displayName": self.display_name,
+                "initiative": "web",
+                "initiativeContext": domain,
+            }))
+            .send()
+            .await
+            .map_err(|e| Error::network(format!("Apple Pay merchant validation failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::payment_error(format!(
+                "Apple Pay merchant validation failed ({}): {}",
+                status, body
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| Error::payment_error(format!("Invalid Apple Pay merchant session: {}", e)))
+    }
+}
+
+/// Whether a validation URL from `onvalidatemerchant` is one of Apple's
+/// payment gateways, so merchant credentials are only ever sent to Apple
+pub fn is_apple_pay_validation_url(url: &str) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = parsed.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    parsed.scheme() == "https"
+        && parsed.port().is_none()
+        && host.ends_with(".apple.com")
+        && host.split('.').count() == 3
+        && (host.starts_with("apple-pay-gateway") || host.starts_with("cn-apple-pay-gateway"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apple_pay_validation_url() {
+        assert!(is_apple_pay_validation_url("https://apple-pay-gateway.apple.com/paymentservices/startSession"));
+        assert!(is_apple_pay_validation_url("https://apple-pay-gateway-nc-pod5.apple.com/paymentservices/paymentSession"));
+        assert!(is_apple_pay_validation_url("https://cn-apple-pay-gateway.apple.com/paymentservices/startSession"));
+        assert!(is_apple_pay_validation_url("https://apple-pay-gateway-cert.apple.com/paymentservices/startSession"));
+
+        assert!(!is_apple_pay_validation_url("http://apple-pay-gateway.apple.com/paymentservices/startSession"));
+        assert!(!is_apple_pay_validation_url("https://apple-pay-gateway.apple.com.evil.example/"));
+        assert!(!is_apple_pay_validation_url("https://apple-pay-gateway.evil.apple.com/"));
+        assert!(!is_apple_pay_validation_url("https://www.apple.com/"));
+        assert!(!is_apple_pay_validation_url("https://apple-pay-gateway.apple.com:8443/"));
+        assert!(!is_apple_pay_validation_url("not a url"));
+    }
+
+    #[test]
+    fn test_validator_needs_merchant_identity() {
+        let mut config = ApplePayConfig::default();
+        assert!(ApplePayMerchantValidator::from_config(&config).unwrap().is_none());
+
+        config.merchant_id = Some("merchant.com.example".to_string());
+        config.merchant_identity_cert = Some("/nonexistent/cert.pem".to_string());
+        config.merchant_identity_key = Some("/nonexistent/key.pem".to_string());
+        assert!(ApplePayMerchantValidator::from_config(&config).is_err());
+
+        config.enabled = false;
+        assert!(ApplePayMerchantValidator::from_config(&config).unwrap().is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! Scheduled reports
+//!
+//! Staff subscribe stakeholders to a report ([`ReportKind`]) on a daily,
+//! weekly or monthly schedule. The `reports` recurring job runs due
+//! subscriptions ([`ReportService::run_due`]): it queries the report for
+//! the period before the run ([`schedule::period`]), renders it as CSV,
+//! XLSX or PDF ([`render`]), stores it as a delivery and emails each
+//! recipient a download link through the notification queue. Files are kept
+//! for `[reports] retention_days`; until then a delivery can be downloaded
+//! by staff and sent again.
+//!
+//! Sales analytics ([`sales`]) are read from daily rollups refreshed by the
+//! `sales_views_refresh` job.
+//!
+//! [`ReportKind`]: crate::models::ReportKind
+
+pub mod render;
+pub mod sales;
+pub mod schedule;
+pub mod service;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+pub use sales::{
+    ChannelReport, Granularity, SalesRange, SalesReport, SalesReportService, SalesViewRefreshJob, TopProductsBy,
+    TopProductsReport,
+};
+pub use service::{ReportJob, ReportRunReport, ReportService, MAX_RECIPIENTS};
+
+/// One cell of a report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ReportValue {
+    Empty,
+    Text(String),
+    Integer(i64),
+    Decimal(Decimal),
+}
+
+impl ReportValue {
+    /// The value as displayed in CSV and PDF files
+    pub fn display(&self) -> String {
+        match self {
+            ReportValue::Empty => String::new(),
+            ReportValue::Text(text) => text.clone(),
+            ReportValue::Integer(n) => n.to_string(),
+            ReportValue::Decimal(d) => d.to_string(),
+        }
+    }
+
+    /// Whether the value is right-aligned in PDF files
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ReportValue::Integer(_) | ReportValue::Decimal(_))
+    }
+}
+
+impl From<String> for ReportValue {
+    fn from(text: String) -> Self {
+        ReportValue::Text(text)
+    }
+}
+
+impl From<&str> for ReportValue {
+    fn from(text: &str) -> Self {
+        ReportValue::Text(text.to_string())
+    }
+}
+
+impl From<i64> for ReportValue {
+    fn from(n: i64) -> Self {
+        ReportValue::Integer(n)
+    }
+}
+
+impl From<i32> for ReportValue {
+    fn from(n: i32) -> Self {
+        ReportValue::Integer(i64::from(n))
+    }
+}
+
+impl From<Decimal> for ReportValue {
+    fn from(d: Decimal) -> Self {
+        ReportValue::Decimal(d)
+    }
+}
+
+impl<T: Into<ReportValue>> From<Option<T>> for ReportValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ReportValue::Empty, Into::into)
+    }
+}
+
+/// A report's rows, ready to render
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportTable {
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<ReportValue>>,
+    /// More rows matched than `[reports] max_rows`
+    pub truncated: bool,
+}
+
+impl ReportTable {
+    pub fn new(columns: Vec<&'static str>) -> Self {
+        Self {
+            columns,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<ReportValue>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+}
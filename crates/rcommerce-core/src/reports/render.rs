@@ -0,0 +1,310 @@
+//! Report files
+//!
+//! Renders a [`ReportTable`] as CSV, XLSX or PDF. Like invoices, the XLSX
+//! and PDF files are written directly: an XLSX workbook is a ZIP archive of
+//! a few XML parts, stored uncompressed here, and PDFs are monospaced text.
+
+use crate::models::ReportFormat;
+use crate::reports::{ReportTable, ReportValue};
+use crate::services::invoice_service::{text_pdf, truncate, LINE_WIDTH};
+use crate::{Error, Result};
+
+/// Widest PDF column, in characters
+const PDF_COLUMN_WIDTH: usize = 24;
+
+/// Render a report; `title` heads PDF files and names the XLSX sheet
+pub fn render(table: &ReportTable, format: ReportFormat, title: &str) -> Result<Vec<u8>> {
+    match format {
+        ReportFormat::Csv => csv(table),
+        ReportFormat::Xlsx => Ok(xlsx(table, title)),
+        ReportFormat::Pdf => Ok(pdf(table, title)),
+    }
+}
+
+fn csv(table: &ReportTable) -> Result<Vec<u8>> {
+    let error = |e: csv::Error| Error::Other(format!("Failed to write report: {}", e));
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    writer.write_record(&table.columns).map_err(error)?;
+    for row in &table.rows {
+        writer.write_record(row.iter().map(csv_cell)).map_err(error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| Error::Other(format!("Failed to write report: {}", e)))
+}
+
+fn csv_cell(value: &ReportValue) -> String {
+    match value {
+        // Keep spreadsheets from running customer-entered text as a formula
+        ReportValue::Text(s) if s.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", s),
+        other => other.display(),
+    }
+}
+
+fn pdf(table: &ReportTable, title: &str) -> Vec<u8> {
+    let widths: Vec<usize> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            table
+                .rows
+                .iter()
+                .map(|row| row[i].display().chars().count())
+                .chain([column.len()])
+                .max()
+                .unwrap_or(0)
+                .min(PDF_COLUMN_WIDTH)
+        })
+        .collect();
+
+    let line = |cells: Vec<(String, bool)>| -> String {
+        let text = cells
+            .iter()
+            .zip(&widths)
+            .map(|((cell, numeric), &width)| {
+                let cell = truncate(cell, width);
+                if *numeric {
+                    format!("{:>width$}", cell, width = width)
+                } else {
+                    format!("{:<width$}", cell, width = width)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        truncate(text.trim_end(), LINE_WIDTH)
+    };
+
+    let mut lines = vec![title.to_string(), String::new()];
+    lines.push(line(table.columns.iter().map(|c| (c.to_string(), false)).collect()));
+    let rule_width = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+    lines.push("-".repeat(rule_width.min(LINE_WIDTH)));
+    for row in &table.rows {
+        lines.push(line(row.iter().map(|value| (value.display(), value.is_numeric())).collect()));
+    }
+    if table.rows.is_empty() {
+        lines.push("No rows".to_string());
+    }
+    if table.truncated {
+        lines.push(String::new());
+        lines.push(format!("Only the first {} rows are included", table.rows.len()));
+    }
+    text_pdf(&lines)
+}
+
+fn xlsx(table: &ReportTable, title: &str) -> Vec<u8> {
+    let mut sheet = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>",
+    );
+    let header: Vec<ReportValue> = table.columns.iter().map(|&column| column.into()).collect();
+    for (r, row) in std::iter::once(&header).chain(&table.rows).enumerate() {
+        sheet.push_str(&format!("<row r=\"{}\">", r + 1));
+        for (c, value) in row.iter().enumerate() {
+            let cell = format!("{}{}", column_name(c), r + 1);
+            match value {
+                ReportValue::Empty => {}
+                ReportValue::Integer(_) | ReportValue::Decimal(_) => {
+                    sheet.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", cell, value.display()));
+                }
+                ReportValue::Text(text) => {
+                    sheet.push_str(&format!(
+                        "<c r=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                        cell,
+                        xml_escape(text)
+                    ));
+                }
+            }
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    // Sheet names are at most 31 characters and can't contain []:*?/\
+    let sheet_name: String = title
+        .chars()
+        .filter(|c| !"[]:*?/\\".contains(*c))
+        .take(31)
+        .collect();
+    let workbook = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+         xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+         <sheets><sheet name=\"{}\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>",
+        xml_escape(&sheet_name)
+    );
+
+    zip_stored(&[
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+             </Types>"
+                .as_bytes(),
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+             </Relationships>"
+                .as_bytes(),
+        ),
+        ("xl/workbook.xml", workbook.as_bytes()),
+        (
+            "xl/_rels/workbook.xml.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+             </Relationships>"
+                .as_bytes(),
+        ),
+        ("xl/worksheets/sheet1.xml", sheet.as_bytes()),
+    ])
+}
+
+/// Spreadsheet column name for a 0-based index: A, B, ..., Z, AA, ...
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Escape text for XML, dropping control characters XML can't hold
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write files into a ZIP archive without compression
+pub(crate) fn zip_stored(files: &[(&str, &[u8])]) -> Vec<u8> {
+    // 1980-01-01 00:00 in MS-DOS date and time format
+    const DOS_DATE: u16 = (1 << 5) | 1;
+    const DOS_TIME: u16 = 0;
+
+    let mut archive = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32fast::hash(data);
+        let size = data.len() as u32;
+
+        archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        archive.extend_from_slice(&0u16.to_le_bytes()); // flags
+        archive.extend_from_slice(&0u16.to_le_bytes()); // stored
+        archive.extend_from_slice(&DOS_TIME.to_le_bytes());
+        archive.extend_from_slice(&DOS_DATE.to_le_bytes());
+        archive.extend_from_slice(&crc.to_le_bytes());
+        archive.extend_from_slice(&size.to_le_bytes());
+        archive.extend_from_slice(&size.to_le_bytes());
+        archive.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&DOS_TIME.to_le_bytes());
+        central.extend_from_slice(&DOS_DATE.to_le_bytes());
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = archive.len() as u32;
+    let central_size = central.len() as u32;
+    archive.extend_from_slice(&central);
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]); // disk numbers
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&central_size.to_le_bytes());
+    archive.extend_from_slice(&central_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn table() -> ReportTable {
+        let mut table = ReportTable::new(vec!["date", "orders", "revenue", "note"]);
+        table.push(vec!["2026-03-01".into(), 12.into(), dec!(1234.50).into(), "=SUM(A1)".into()]);
+        table.push(vec!["2026-03-02".into(), 3.into(), dec!(80).into(), ReportValue::Empty]);
+        table
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = String::from_utf8(render(&table(), ReportFormat::Csv, "Sales").unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, ["date,orders,revenue,note", "2026-03-01,12,1234.50,'=SUM(A1)", "2026-03-02,3,80,"]);
+    }
+
+    #[test]
+    fn test_xlsx_archive() {
+        let xlsx = render(&table(), ReportFormat::Xlsx, "Sales: March").unwrap();
+        assert!(xlsx.starts_with(b"PK\x03\x04"));
+
+        // The end record points at the central directory, which lists all parts
+        let end = xlsx.len() - 22;
+        assert_eq!(&xlsx[end..end + 4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([xlsx[end + 10], xlsx[end + 11]]), 5);
+        let offset = u32::from_le_bytes(xlsx[end + 16..end + 20].try_into().unwrap()) as usize;
+        assert_eq!(&xlsx[offset..offset + 4], b"PK\x01\x02");
+
+        let text = String::from_utf8_lossy(&xlsx);
+        assert!(text.contains("<sheet name=\"Sales March\""));
+        assert!(text.contains("<c r=\"B2\"><v>12</v></c>"));
+        assert!(text.contains("<c r=\"D2\" t=\"inlineStr\"><is><t xml:space=\"preserve\">=SUM(A1)</t></is></c>"));
+    }
+
+    #[test]
+    fn test_pdf_layout() {
+        let pdf = String::from_utf8(render(&table(), ReportFormat::Pdf, "Sales summary").unwrap()).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains("(Sales summary) Tj"));
+        assert!(pdf.contains("(2026-03-01      12  1234.50  =SUM\\(A1\\)) Tj"));
+    }
+
+    #[test]
+    fn test_column_name() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+}
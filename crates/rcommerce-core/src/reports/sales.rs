@@ -0,0 +1,532 @@
+//! Sales analytics
+//!
+//! Revenue over time, top products, conversion by channel, refund rates and
+//! average order value, read from daily rollups (materialized views, see
+//! migration 067) instead of the orders table. The `sales_views_refresh`
+//! recurring job refreshes them, so figures lag orders by up to
+//! `[reports] sales_refresh_interval_secs`; every report says when the
+//! views were last refreshed. Amounts are in the base currency and days are
+//! UTC dates.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::ReportsConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::repository::SalesReportRepository;
+use crate::{Error, Result};
+
+/// Longest range a report covers
+pub const MAX_RANGE_DAYS: i64 = 732;
+
+/// Most products in a top products report
+pub const MAX_TOP_PRODUCTS: i64 = 100;
+
+/// The channel of storefront orders, whose conversion is measured against
+/// storefront sessions
+const STOREFRONT_CHANNEL: &str = "web";
+
+/// Length of the periods a report is broken into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// First day of the period a day falls in
+    pub fn period_start(&self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => day,
+            Granularity::Week => day - Duration::days(i64::from(day.weekday().num_days_from_monday())),
+            Granularity::Month => day.with_day(1).unwrap_or(day),
+        }
+    }
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" | "daily" => Ok(Granularity::Day),
+            "week" | "weekly" => Ok(Granularity::Week),
+            "month" | "monthly" => Ok(Granularity::Month),
+            _ => Err(format!("Unknown granularity '{}' (day, week, month)", s)),
+        }
+    }
+}
+
+/// Days a report covers, both inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SalesRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl SalesRange {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Result<Self> {
+        if from > to {
+            return Err(Error::validation("from must not be after to"));
+        }
+        if (to - from).num_days() >= MAX_RANGE_DAYS {
+            return Err(Error::validation(format!("A report covers at most {} days", MAX_RANGE_DAYS)));
+        }
+        Ok(Self { from, to })
+    }
+
+    /// The last `days` days, up to and including `today`
+    pub fn last_days(days: i64, today: NaiveDate) -> Result<Self> {
+        if days < 1 {
+            return Err(Error::validation("days must be at least 1"));
+        }
+        Self::new(today - Duration::days(days - 1), today)
+    }
+}
+
+/// Which figure top products are ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TopProductsBy {
+    #[default]
+    Revenue,
+    Units,
+}
+
+/// One day of one channel's sales, as stored in `sales_daily`
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct DailySales {
+    pub day: NaiveDate,
+    pub channel: String,
+    pub orders: i64,
+    pub paid_orders: i64,
+    pub items_sold: i64,
+    pub gross_revenue: Decimal,
+    pub refunded_orders: i64,
+    pub refunded_amount: Decimal,
+}
+
+/// Sales added up over days and channels
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SalesTotals {
+    pub orders: i64,
+    pub paid_orders: i64,
+    pub items_sold: i64,
+    pub gross_revenue: Decimal,
+    pub refunded_orders: i64,
+    pub refunded_amount: Decimal,
+}
+
+impl SalesTotals {
+    pub fn add(&mut self, day: &DailySales) {
+        self.orders += day.orders;
+        self.paid_orders += day.paid_orders;
+        self.items_sold += day.items_sold;
+        self.gross_revenue += day.gross_revenue;
+        self.refunded_orders += day.refunded_orders;
+        self.refunded_amount += day.refunded_amount;
+    }
+
+    pub fn net_revenue(&self) -> Decimal {
+        self.gross_revenue - self.refunded_amount
+    }
+
+    /// Gross revenue per paid order
+    pub fn average_order_value(&self) -> Decimal {
+        if self.paid_orders > 0 {
+            (self.gross_revenue / Decimal::from(self.paid_orders)).round_dp(2)
+        } else {
+            Decimal::ZERO
+        }
+    }
+}
+
+/// `part` as a percentage of `whole`, to one decimal; None if `whole` is zero
+fn percentage(part: f64, whole: f64) -> Option<f64> {
+    (whole > 0.0).then(|| (part * 1000.0 / whole).round() / 10.0)
+}
+
+fn decimal_percentage(part: Decimal, whole: Decimal) -> Option<f64> {
+    use rust_decimal::prelude::ToPrimitive;
+    percentage(part.to_f64().unwrap_or(0.0), whole.to_f64().unwrap_or(0.0))
+}
+
+/// Revenue of a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RevenueFigures {
+    pub orders: i64,
+    pub paid_orders: i64,
+    pub items_sold: i64,
+    pub gross_revenue: Decimal,
+    pub refunded_amount: Decimal,
+    /// Gross revenue less refunds
+    pub net_revenue: Decimal,
+}
+
+impl From<&SalesTotals> for RevenueFigures {
+    fn from(totals: &SalesTotals) -> Self {
+        Self {
+            orders: totals.orders,
+            paid_orders: totals.paid_orders,
+            items_sold: totals.items_sold,
+            gross_revenue: totals.gross_revenue,
+            refunded_amount: totals.refunded_amount,
+            net_revenue: totals.net_revenue(),
+        }
+    }
+}
+
+/// Refunds of a period's orders
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefundFigures {
+    pub paid_orders: i64,
+    pub refunded_orders: i64,
+    /// Percentage of paid orders with a refund
+    pub refund_rate: Option<f64>,
+    pub gross_revenue: Decimal,
+    pub refunded_amount: Decimal,
+    /// Percentage of gross revenue refunded
+    pub refunded_revenue_rate: Option<f64>,
+}
+
+impl From<&SalesTotals> for RefundFigures {
+    fn from(totals: &SalesTotals) -> Self {
+        Self {
+            paid_orders: totals.paid_orders,
+            refunded_orders: totals.refunded_orders,
+            refund_rate: percentage(totals.refunded_orders as f64, totals.paid_orders as f64),
+            gross_revenue: totals.gross_revenue,
+            refunded_amount: totals.refunded_amount,
+            refunded_revenue_rate: decimal_percentage(totals.refunded_amount, totals.gross_revenue),
+        }
+    }
+}
+
+/// Average order value of a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderValueFigures {
+    pub paid_orders: i64,
+    pub gross_revenue: Decimal,
+    pub average_order_value: Decimal,
+}
+
+impl From<&SalesTotals> for OrderValueFigures {
+    fn from(totals: &SalesTotals) -> Self {
+        Self {
+            paid_orders: totals.paid_orders,
+            gross_revenue: totals.gross_revenue,
+            average_order_value: totals.average_order_value(),
+        }
+    }
+}
+
+/// The figures of one period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SalesPeriod<T> {
+    /// First day of the period
+    pub period: NaiveDate,
+    #[serde(flatten)]
+    pub figures: T,
+}
+
+/// A report broken into periods, with the total over the range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SalesReport<T> {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub granularity: Granularity,
+    /// When the views the report reads were last refreshed
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub total: T,
+    /// Periods with orders, oldest first
+    pub periods: Vec<SalesPeriod<T>>,
+}
+
+impl<T: for<'a> From<&'a SalesTotals>> SalesReport<T> {
+    pub fn new(range: SalesRange, granularity: Granularity, refreshed_at: Option<DateTime<Utc>>, days: &[DailySales]) -> Self {
+        let mut total = SalesTotals::default();
+        let mut periods: BTreeMap<NaiveDate, SalesTotals> = BTreeMap::new();
+        for day in days {
+            total.add(day);
+            periods.entry(granularity.period_start(day.day)).or_default().add(day);
+        }
+        Self {
+            from: range.from,
+            to: range.to,
+            granularity,
+            refreshed_at,
+            total: T::from(&total),
+            periods: periods
+                .iter()
+                .map(|(period, totals)| SalesPeriod { period: *period, figures: T::from(totals) })
+                .collect(),
+        }
+    }
+}
+
+/// Orders and conversion of one channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelConversion {
+    pub channel: String,
+    pub orders: i64,
+    pub paid_orders: i64,
+    /// Percentage of orders that were paid
+    pub payment_rate: Option<f64>,
+    pub gross_revenue: Decimal,
+    pub average_order_value: Decimal,
+    /// Storefront sessions; only for the `web` channel
+    pub sessions: Option<i64>,
+    /// Percentage of storefront sessions with a paid order
+    pub conversion_rate: Option<f64>,
+}
+
+/// Conversion by channel over a range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub refreshed_at: Option<DateTime<Utc>>,
+    /// Channels by gross revenue, highest first
+    pub channels: Vec<ChannelConversion>,
+}
+
+impl ChannelReport {
+    /// `sessions` are the storefront's, credited to the `web` channel
+    pub fn new(range: SalesRange, refreshed_at: Option<DateTime<Utc>>, days: &[DailySales], sessions: f64) -> Self {
+        let mut totals: BTreeMap<&str, SalesTotals> = BTreeMap::new();
+        for day in days {
+            totals.entry(day.channel.as_str()).or_default().add(day);
+        }
+        let mut channels: Vec<ChannelConversion> = totals
+            .into_iter()
+            .map(|(channel, totals)| {
+                let sessions = (channel == STOREFRONT_CHANNEL).then_some(sessions);
+                ChannelConversion {
+                    channel: channel.to_string(),
+                    orders: totals.orders,
+                    paid_orders: totals.paid_orders,
+                    payment_rate: percentage(totals.paid_orders as f64, totals.orders as f64),
+                    gross_revenue: totals.gross_revenue,
+                    average_order_value: totals.average_order_value(),
+                    sessions: sessions.map(|sessions| sessions.round() as i64),
+                    conversion_rate: sessions.and_then(|sessions| percentage(totals.paid_orders as f64, sessions)),
+                }
+            })
+            .collect();
+        channels.sort_by(|a, b| b.gross_revenue.cmp(&a.gross_revenue).then_with(|| a.channel.cmp(&b.channel)));
+        Self { from: range.from, to: range.to, refreshed_at, channels }
+    }
+}
+
+/// A product's sales over a range
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct TopProduct {
+    pub product_id: Uuid,
+    pub title: String,
+    pub units: i64,
+    pub orders: i64,
+    pub revenue: Decimal,
+}
+
+/// Best selling products over a range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TopProductsReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub by: TopProductsBy,
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub products: Vec<TopProduct>,
+}
+
+/// Sales reports over the daily rollups
+pub struct SalesReportService<R: SalesReportRepository> {
+    repository: R,
+    config: ReportsConfig,
+}
+
+impl<R: SalesReportRepository> SalesReportService<R> {
+    pub fn new(repository: R, config: ReportsConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn config(&self) -> &ReportsConfig {
+        &self.config
+    }
+
+    /// Orders, items and revenue per period
+    pub async fn revenue(&self, range: SalesRange, granularity: Granularity) -> Result<SalesReport<RevenueFigures>> {
+        let days = self.repository.daily_sales(range.from, range.to).await?;
+        Ok(SalesReport::new(range, granularity, self.repository.refreshed_at().await?, &days))
+    }
+
+    /// Refunded orders and amounts per period, by the day of their order
+    pub async fn refunds(&self, range: SalesRange, granularity: Granularity) -> Result<SalesReport<RefundFigures>> {
+        let days = self.repository.daily_sales(range.from, range.to).await?;
+        Ok(SalesReport::new(range, granularity, self.repository.refreshed_at().await?, &days))
+    }
+
+    /// Average order value per period
+    pub async fn average_order_value(
+        &self,
+        range: SalesRange,
+        granularity: Granularity,
+    ) -> Result<SalesReport<OrderValueFigures>> {
+        let days = self.repository.daily_sales(range.from, range.to).await?;
+        Ok(SalesReport::new(range, granularity, self.repository.refreshed_at().await?, &days))
+    }
+
+    /// Orders, payment and storefront conversion per channel
+    pub async fn channels(&self, range: SalesRange) -> Result<ChannelReport> {
+        let days = self.repository.daily_sales(range.from, range.to).await?;
+        let sessions = self.repository.storefront_sessions(range.from, range.to).await?;
+        Ok(ChannelReport::new(range, self.repository.refreshed_at().await?, &days, sessions))
+    }
+
+    /// Best selling products
+    pub async fn top_products(&self, range: SalesRange, by: TopProductsBy, limit: i64) -> Result<TopProductsReport> {
+        if !(1..=MAX_TOP_PRODUCTS).contains(&limit) {
+            return Err(Error::validation(format!("limit must be between 1 and {}", MAX_TOP_PRODUCTS)));
+        }
+        let products = self.repository.top_products(range.from, range.to, by, limit).await?;
+        Ok(TopProductsReport {
+            from: range.from,
+            to: range.to,
+            by,
+            refreshed_at: self.repository.refreshed_at().await?,
+            products,
+        })
+    }
+
+    /// Refresh the views now; returns when
+    pub async fn refresh(&self) -> Result<DateTime<Utc>> {
+        self.repository.refresh().await
+    }
+}
+
+/// The `sales_views_refresh` recurring job
+pub struct SalesViewRefreshJob<R: SalesReportRepository> {
+    sales: Arc<SalesReportService<R>>,
+}
+
+impl<R: SalesReportRepository> SalesViewRefreshJob<R> {
+    pub fn new(sales: Arc<SalesReportService<R>>) -> Self {
+        Self { sales }
+    }
+}
+
+#[async_trait]
+impl<R: SalesReportRepository + 'static> RecurringJob for SalesViewRefreshJob<R> {
+    fn name(&self) -> &str {
+        "sales_views_refresh"
+    }
+
+    fn description(&self) -> &str {
+        "Refresh the daily sales rollups behind the sales reports"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.sales.config().sales_refresh_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        let refreshed_at = self.sales.refresh().await?;
+        Ok(format!("refreshed at {}", refreshed_at.format("%Y-%m-%d %H:%M:%S UTC")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn day(d: &str, channel: &str, orders: i64, paid: i64, revenue: Decimal, refunded: i64, refund: Decimal) -> DailySales {
+        DailySales {
+            day: date(d),
+            channel: channel.to_string(),
+            orders,
+            paid_orders: paid,
+            items_sold: paid * 2,
+            gross_revenue: revenue,
+            refunded_orders: refunded,
+            refunded_amount: refund,
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2026-10-15 is a Thursday
+        assert_eq!(Granularity::Day.period_start(date("2026-10-15")), date("2026-10-15"));
+        assert_eq!(Granularity::Week.period_start(date("2026-10-15")), date("2026-10-12"));
+        assert_eq!(Granularity::Week.period_start(date("2026-10-12")), date("2026-10-12"));
+        assert_eq!(Granularity::Month.period_start(date("2026-10-15")), date("2026-10-01"));
+    }
+
+    #[test]
+    fn test_range() {
+        let range = SalesRange::last_days(30, date("2026-10-15")).unwrap();
+        assert_eq!(range.from, date("2026-09-16"));
+        assert!(SalesRange::new(date("2026-10-02"), date("2026-10-01")).is_err());
+        assert!(SalesRange::new(date("2020-01-01"), date("2026-01-01")).is_err());
+        assert!(SalesRange::last_days(0, date("2026-10-15")).is_err());
+    }
+
+    #[test]
+    fn test_report_periods() {
+        let days = vec![
+            day("2026-10-12", "web", 4, 3, dec!(300), 1, dec!(50)),
+            day("2026-10-13", "amazon", 2, 2, dec!(100), 0, dec!(0)),
+            day("2026-10-19", "web", 1, 0, dec!(0), 0, dec!(0)),
+        ];
+        let range = SalesRange::new(date("2026-10-01"), date("2026-10-31")).unwrap();
+
+        let revenue: SalesReport<RevenueFigures> = SalesReport::new(range, Granularity::Week, None, &days);
+        assert_eq!(revenue.periods.len(), 2);
+        assert_eq!(revenue.periods[0].period, date("2026-10-12"));
+        assert_eq!(revenue.periods[0].figures.orders, 6);
+        assert_eq!(revenue.periods[0].figures.net_revenue, dec!(350));
+        assert_eq!(revenue.total.paid_orders, 5);
+
+        let refunds: SalesReport<RefundFigures> = SalesReport::new(range, Granularity::Month, None, &days);
+        assert_eq!(refunds.total.refund_rate, Some(20.0));
+        assert_eq!(refunds.total.refunded_revenue_rate, Some(12.5));
+        assert_eq!(refunds.periods.len(), 1);
+
+        let order_value: SalesReport<OrderValueFigures> = SalesReport::new(range, Granularity::Day, None, &days);
+        assert_eq!(order_value.total.average_order_value, dec!(80));
+        assert_eq!(order_value.periods[2].figures.average_order_value, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_channel_report() {
+        let days = vec![
+            day("2026-10-12", "web", 4, 3, dec!(300), 0, dec!(0)),
+            day("2026-10-13", "web", 1, 1, dec!(100), 0, dec!(0)),
+            day("2026-10-13", "ebay", 2, 1, dec!(500), 0, dec!(0)),
+        ];
+        let range = SalesRange::new(date("2026-10-12"), date("2026-10-13")).unwrap();
+        let report = ChannelReport::new(range, None, &days, 199.6);
+
+        assert_eq!(report.channels[0].channel, "ebay");
+        assert_eq!(report.channels[0].payment_rate, Some(50.0));
+        assert_eq!(report.channels[0].sessions, None);
+        assert_eq!(report.channels[0].conversion_rate, None);
+        assert_eq!(report.channels[1].channel, "web");
+        assert_eq!(report.channels[1].sessions, Some(200));
+        assert_eq!(report.channels[1].conversion_rate, Some(2.0));
+        assert_eq!(report.channels[1].average_order_value, dec!(100));
+    }
+}
@@ -0,0 +1,124 @@
+//! When subscriptions run and which period their reports cover
+//!
+//! All times are UTC. A daily report covers the day before it runs, a
+//! weekly one the seven days before and a monthly one the month before,
+//! each ending at midnight of the day it runs.
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::models::{ReportSchedule, ReportSubscription};
+
+/// When a subscription sends its report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleSpec {
+    pub schedule: ReportSchedule,
+    /// 0-23
+    pub hour: u32,
+    /// ISO weekday, 1 = Monday (weekly only)
+    pub weekday: u32,
+    /// 1-28 (monthly only)
+    pub day_of_month: u32,
+}
+
+impl From<&ReportSubscription> for ScheduleSpec {
+    fn from(subscription: &ReportSubscription) -> Self {
+        Self {
+            schedule: subscription.schedule,
+            hour: subscription.hour as u32,
+            weekday: subscription.weekday as u32,
+            day_of_month: subscription.day_of_month as u32,
+        }
+    }
+}
+
+impl ScheduleSpec {
+    /// The first run strictly after `after`
+    pub fn next_run(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let date = after.date_naive();
+        let candidate = match self.schedule {
+            ReportSchedule::Daily => date,
+            ReportSchedule::Weekly => {
+                let today = date.weekday().number_from_monday();
+                date + Duration::days(i64::from((self.weekday + 7 - today) % 7))
+            }
+            ReportSchedule::Monthly => date.with_day(self.day_of_month).unwrap_or(date),
+        };
+
+        let mut run = self.at_hour(candidate);
+        while run <= after {
+            let next = match self.schedule {
+                ReportSchedule::Daily => run.date_naive() + Duration::days(1),
+                ReportSchedule::Weekly => run.date_naive() + Duration::days(7),
+                ReportSchedule::Monthly => run.date_naive() + Months::new(1),
+            };
+            run = self.at_hour(next);
+        }
+        run
+    }
+
+    fn at_hour(&self, date: NaiveDate) -> DateTime<Utc> {
+        let time = NaiveTime::from_hms_opt(self.hour.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+        Utc.from_utc_datetime(&date.and_time(time))
+    }
+}
+
+/// The period a report run at `run_at` covers: from the start of the
+/// previous day, week or month up to midnight of the run's day
+pub fn period(schedule: ReportSchedule, run_at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end = run_at.date_naive();
+    let start = match schedule {
+        ReportSchedule::Daily => end - Duration::days(1),
+        ReportSchedule::Weekly => end - Duration::days(7),
+        ReportSchedule::Monthly => end - Months::new(1),
+    };
+    (
+        Utc.from_utc_datetime(&start.and_time(NaiveTime::MIN)),
+        Utc.from_utc_datetime(&end.and_time(NaiveTime::MIN)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn spec(schedule: ReportSchedule) -> ScheduleSpec {
+        ScheduleSpec { schedule, hour: 6, weekday: 1, day_of_month: 1 }
+    }
+
+    #[test]
+    fn test_next_run() {
+        let daily = spec(ReportSchedule::Daily);
+        assert_eq!(daily.next_run(at("2026-03-10T05:00:00Z")), at("2026-03-10T06:00:00Z"));
+        assert_eq!(daily.next_run(at("2026-03-10T06:00:00Z")), at("2026-03-11T06:00:00Z"));
+
+        // 2026-03-11 is a Wednesday
+        let weekly = spec(ReportSchedule::Weekly);
+        assert_eq!(weekly.next_run(at("2026-03-11T12:00:00Z")), at("2026-03-16T06:00:00Z"));
+        assert_eq!(weekly.next_run(at("2026-03-16T07:00:00Z")), at("2026-03-23T06:00:00Z"));
+
+        let monthly = ScheduleSpec { day_of_month: 15, ..spec(ReportSchedule::Monthly) };
+        assert_eq!(monthly.next_run(at("2026-03-10T00:00:00Z")), at("2026-03-15T06:00:00Z"));
+        assert_eq!(monthly.next_run(at("2026-12-20T00:00:00Z")), at("2027-01-15T06:00:00Z"));
+    }
+
+    #[test]
+    fn test_period() {
+        let run_at = at("2026-03-01T06:00:00Z");
+        assert_eq!(
+            period(ReportSchedule::Daily, run_at),
+            (at("2026-02-28T00:00:00Z"), at("2026-03-01T00:00:00Z"))
+        );
+        assert_eq!(
+            period(ReportSchedule::Weekly, run_at),
+            (at("2026-02-22T00:00:00Z"), at("2026-03-01T00:00:00Z"))
+        );
+        assert_eq!(
+            period(ReportSchedule::Monthly, run_at),
+            (at("2026-02-01T00:00:00Z"), at("2026-03-01T00:00:00Z"))
+        );
+    }
+}
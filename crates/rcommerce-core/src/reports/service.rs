@@ -0,0 +1,485 @@
+//! Report subscriptions and deliveries
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::render::render;
+use super::schedule::{period, ScheduleSpec};
+use super::ReportTable;
+use crate::common::validation::validate_email;
+use crate::config::ReportsConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::models::{
+    CreateReportSubscriptionRequest, NewReportDelivery, ReportDelivery, ReportDeliveryStatus, ReportFormat,
+    ReportKind, ReportSubscription, ResendReportRequest, UpdateReportSubscriptionRequest,
+};
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::repository::{NotificationRepository, ReportRepository};
+use crate::{Error, Result};
+
+/// Recipients per subscription at most
+pub const MAX_RECIPIENTS: usize = 50;
+
+/// Outcome of a run of the `reports` job
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportRunReport {
+    /// Due subscriptions that ran
+    pub subscriptions: usize,
+    /// Reports that could not be generated or emailed
+    pub failed: usize,
+    /// Expired report files dropped
+    pub purged: u64,
+}
+
+impl std::fmt::Display for ReportRunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sent {} reports", self.subscriptions - self.failed)?;
+        if self.failed > 0 {
+            write!(f, " ({} failed)", self.failed)?;
+        }
+        if self.purged > 0 {
+            write!(f, ", purged {} expired files", self.purged)?;
+        }
+        Ok(())
+    }
+}
+
+/// Report subscription service
+pub struct ReportService<R: ReportRepository> {
+    repository: R,
+    config: ReportsConfig,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+}
+
+impl<R: ReportRepository> ReportService<R> {
+    pub fn new(repository: R, config: ReportsConfig) -> Self {
+        Self {
+            repository,
+            config,
+            notifications: None,
+        }
+    }
+
+    /// Queue report emails through the notification queue
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    pub fn config(&self) -> &ReportsConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub async fn create_subscription(
+        &self,
+        actor_id: Option<Uuid>,
+        request: CreateReportSubscriptionRequest,
+    ) -> Result<ReportSubscription> {
+        validate_subscription(&request)?;
+        let next_run_at = schedule_of(&request).next_run(Utc::now());
+        self.repository.create_subscription(&request, next_run_at, actor_id).await
+    }
+
+    /// Change a subscription; its next run is rescheduled from now
+    pub async fn update_subscription(
+        &self,
+        id: Uuid,
+        request: UpdateReportSubscriptionRequest,
+    ) -> Result<ReportSubscription> {
+        let current = self.get_subscription(id).await?;
+        let subscription = CreateReportSubscriptionRequest {
+            name: request.name.unwrap_or(current.name),
+            report: request.report.unwrap_or(current.report),
+            schedule: request.schedule.unwrap_or(current.schedule),
+            format: request.format.unwrap_or(current.format),
+            recipients: request.recipients.unwrap_or(current.recipients),
+            hour: request.hour.unwrap_or(current.hour),
+            weekday: request.weekday.unwrap_or(current.weekday),
+            day_of_month: request.day_of_month.unwrap_or(current.day_of_month),
+            is_active: request.is_active.unwrap_or(current.is_active),
+        };
+        validate_subscription(&subscription)?;
+
+        let next_run_at = schedule_of(&subscription).next_run(Utc::now());
+        self.repository
+            .update_subscription(id, &subscription, next_run_at)
+            .await?
+            .ok_or_else(|| Error::not_found("Report subscription not found"))
+    }
+
+    pub async fn get_subscription(&self, id: Uuid) -> Result<ReportSubscription> {
+        self.repository
+            .find_subscription(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Report subscription not found"))
+    }
+
+    pub async fn list_subscriptions(&self) -> Result<Vec<ReportSubscription>> {
+        self.repository.list_subscriptions().await
+    }
+
+    /// Delete a subscription; its deliveries are kept as history
+    pub async fn delete_subscription(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete_subscription(id).await? {
+            return Err(Error::not_found("Report subscription not found"));
+        }
+        Ok(())
+    }
+
+    /// Query a report; low stock ignores the period
+    pub async fn generate(&self, report: ReportKind, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<ReportTable> {
+        let limit = i64::from(self.config.max_rows);
+        match report {
+            ReportKind::SalesSummary => self.repository.sales_summary(from, to, limit).await,
+            ReportKind::TaxLiability => self.repository.tax_liability(from, to, limit).await,
+            ReportKind::LowStock => self.repository.low_stock(limit).await,
+            ReportKind::FailedPayments => self.repository.failed_payments(from, to, limit).await,
+            ReportKind::CheckoutFunnel => self.repository.checkout_funnel(from, to).await,
+        }
+    }
+
+    /// Run a subscription now, without changing its schedule
+    pub async fn run_now(&self, id: Uuid) -> Result<ReportDelivery> {
+        let subscription = self.get_subscription(id).await?;
+        self.run_subscription(&subscription, Utc::now()).await
+    }
+
+    /// Run due subscriptions and drop expired files
+    pub async fn run_due(&self) -> Result<ReportRunReport> {
+        let now = Utc::now();
+        let due = self
+            .repository
+            .due_subscriptions(now, i64::from(self.config.batch_size))
+            .await?;
+        let mut report = ReportRunReport::default();
+
+        for subscription in due {
+            // Cover the period before the scheduled run, even when the job runs late
+            let delivery = self.run_subscription(&subscription, subscription.next_run_at).await;
+            let next_run_at = ScheduleSpec::from(&subscription).next_run(now);
+            self.repository.schedule_next(subscription.id, now, next_run_at).await?;
+
+            report.subscriptions += 1;
+            match delivery {
+                Ok(delivery) if delivery.status == ReportDeliveryStatus::Sent => {}
+                Ok(delivery) => {
+                    tracing::warn!(
+                        "Report subscription {} failed: {}",
+                        subscription.id,
+                        delivery.error.unwrap_or_default()
+                    );
+                    report.failed += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Report subscription {} failed: {}", subscription.id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        report.purged = self.repository.purge_expired().await?;
+        Ok(report)
+    }
+
+    pub async fn get_delivery(&self, id: Uuid) -> Result<ReportDelivery> {
+        self.repository
+            .find_delivery(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Report delivery not found"))
+    }
+
+    /// Delivery history, newest first
+    pub async fn list_deliveries(&self, subscription_id: Option<Uuid>, limit: i64) -> Result<Vec<ReportDelivery>> {
+        self.repository.list_deliveries(subscription_id, limit).await
+    }
+
+    /// Email a delivered report again, to its recipients or others
+    pub async fn resend(&self, id: Uuid, request: ResendReportRequest) -> Result<ReportDelivery> {
+        let delivery = self.get_delivery(id).await?;
+        if !delivery.available {
+            return Err(match delivery.status {
+                ReportDeliveryStatus::Failed if delivery.size_bytes == 0 => Error::validation(
+                    "The report could not be generated; run the subscription again",
+                ),
+                _ => Error::validation("The report file has expired; run the subscription again"),
+            });
+        }
+
+        let recipients = request.recipients.unwrap_or_else(|| delivery.recipients.clone());
+        validate_recipients(&recipients)?;
+        let name = match delivery.subscription_id {
+            Some(subscription_id) => self.repository.find_subscription(subscription_id).await?.map(|s| s.name),
+            None => None,
+        };
+        let name = name.unwrap_or_else(|| delivery.report.title().to_string());
+        self.send(&delivery, &name, &recipients).await
+    }
+
+    /// A delivery's file, for staff
+    pub async fn download(&self, id: Uuid) -> Result<(ReportDelivery, Vec<u8>)> {
+        let delivery = self.get_delivery(id).await?;
+        self.content(delivery).await
+    }
+
+    /// A delivery's file from the link in a report email
+    pub async fn download_by_token(&self, token: &str) -> Result<(ReportDelivery, Vec<u8>)> {
+        let delivery = self
+            .repository
+            .find_delivery_by_token(token)
+            .await?
+            .ok_or_else(|| Error::not_found("Report not found"))?;
+        if delivery.expires_at <= Utc::now() {
+            return Err(Error::not_found("This report link has expired"));
+        }
+        self.content(delivery).await
+    }
+
+    /// Link in report emails
+    pub fn download_url(&self, delivery: &ReportDelivery) -> String {
+        format!(
+            "{}/api/v1/reports/download/{}",
+            self.config.public_url.trim_end_matches('/'),
+            delivery.download_token
+        )
+    }
+
+    async fn content(&self, delivery: ReportDelivery) -> Result<(ReportDelivery, Vec<u8>)> {
+        match self.repository.delivery_content(delivery.id).await? {
+            Some(content) => Ok((delivery, content)),
+            None => Err(Error::not_found("The report file is no longer available")),
+        }
+    }
+
+    /// Generate, store and email a subscription's report as of `run_at`
+    async fn run_subscription(&self, subscription: &ReportSubscription, run_at: DateTime<Utc>) -> Result<ReportDelivery> {
+        let (from, to) = period(subscription.schedule, run_at);
+        let generated = match self.generate(subscription.report, from, to).await {
+            Ok(table) => render(&table, subscription.format, &subscription.name).map(|content| (table.rows.len(), content)),
+            Err(e) => Err(e),
+        };
+
+        let covered = subscription.report.has_period().then_some((from, to));
+        let mut delivery = NewReportDelivery {
+            subscription_id: Some(subscription.id),
+            report: subscription.report,
+            format: subscription.format,
+            period_start: covered.map(|(from, _)| from),
+            period_end: covered.map(|(_, to)| to),
+            recipients: subscription.recipients.clone(),
+            status: ReportDeliveryStatus::Sent,
+            error: None,
+            filename: filename(subscription.report, subscription.format, covered, run_at),
+            row_count: 0,
+            content: None,
+            download_token: download_token(),
+            expires_at: Utc::now() + Duration::days(i64::from(self.config.retention_days)),
+        };
+        match generated {
+            Ok((rows, content)) => {
+                delivery.row_count = rows as i32;
+                delivery.content = Some(content);
+            }
+            Err(e) => {
+                delivery.status = ReportDeliveryStatus::Failed;
+                delivery.error = Some(e.to_string());
+            }
+        }
+
+        let delivery = self.repository.create_delivery(&delivery).await?;
+        if delivery.status == ReportDeliveryStatus::Failed {
+            return Ok(delivery);
+        }
+        self.send(&delivery, &subscription.name, &subscription.recipients).await
+    }
+
+    /// Queue an email with the download link for each recipient
+    async fn send(&self, delivery: &ReportDelivery, name: &str, recipients: &[String]) -> Result<ReportDelivery> {
+        let Some(ref notifications) = self.notifications else {
+            return self
+                .repository
+                .record_failure(delivery.id, "No notification queue is configured")
+                .await;
+        };
+
+        let url = self.download_url(delivery);
+        for recipient in recipients {
+            let notification =
+                NotificationFactory::report_ready(delivery, name, &url, Recipient::email(recipient.clone(), None));
+            if let Err(e) = notifications.create(&notification).await {
+                return self
+                    .repository
+                    .record_failure(delivery.id, &format!("Failed to queue report email to {}: {}", recipient, e))
+                    .await;
+            }
+        }
+        self.repository.record_sent(delivery.id).await
+    }
+}
+
+/// Check a subscription's name, schedule and recipients
+pub fn validate_subscription(request: &CreateReportSubscriptionRequest) -> Result<()> {
+    request.validate().map_err(|e| Error::validation(e.to_string()))?;
+    if request.name.trim().is_empty() {
+        return Err(Error::validation("Report subscriptions need a name"));
+    }
+    validate_recipients(&request.recipients)
+}
+
+/// Recipients need valid, distinct email addresses
+pub fn validate_recipients(recipients: &[String]) -> Result<()> {
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(Error::validation(format!("Reports need 1 to {} recipients", MAX_RECIPIENTS)));
+    }
+    let mut seen = HashSet::new();
+    for recipient in recipients {
+        if !validate_email(recipient) {
+            return Err(Error::validation(format!("Invalid recipient email: {}", recipient)));
+        }
+        if !seen.insert(recipient.to_lowercase()) {
+            return Err(Error::validation(format!("Recipient {} is listed more than once", recipient)));
+        }
+    }
+    Ok(())
+}
+
+fn schedule_of(request: &CreateReportSubscriptionRequest) -> ScheduleSpec {
+    ScheduleSpec {
+        schedule: request.schedule,
+        hour: request.hour as u32,
+        weekday: request.weekday as u32,
+        day_of_month: request.day_of_month as u32,
+    }
+}
+
+/// e.g. `sales-summary-2026-02-01-to-2026-02-28.xlsx`, or
+/// `low-stock-2026-03-01.csv` for snapshots
+fn filename(
+    report: ReportKind,
+    format: ReportFormat,
+    covered: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    run_at: DateTime<Utc>,
+) -> String {
+    let name = report.as_str().replace('_', "-");
+    let dates = match covered {
+        Some((from, to)) => {
+            let last_day = (to - Duration::days(1)).date_naive();
+            if from.date_naive() == last_day {
+                last_day.to_string()
+            } else {
+                format!("{}-to-{}", from.date_naive(), last_day)
+            }
+        }
+        None => run_at.date_naive().to_string(),
+    };
+    format!("{}-{}.{}", name, dates, format.extension())
+}
+
+fn download_token() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+/// The `reports` recurring job
+pub struct ReportJob<R: ReportRepository> {
+    reports: Arc<ReportService<R>>,
+}
+
+impl<R: ReportRepository> ReportJob<R> {
+    pub fn new(reports: Arc<ReportService<R>>) -> Self {
+        Self { reports }
+    }
+}
+
+#[async_trait]
+impl<R: ReportRepository + 'static> RecurringJob for ReportJob<R> {
+    fn name(&self) -> &str {
+        "reports"
+    }
+
+    fn description(&self) -> &str {
+        "Generate scheduled reports and email them to their recipients"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.reports.config().run_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        let report = self.reports.run_due().await?;
+        // Fail the run so `rcommerce jobs list` points at the delivery history
+        if report.failed > 0 {
+            return Err(Error::Other(report.to_string()));
+        }
+        Ok(report.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReportSchedule;
+
+    fn request(recipients: &[&str]) -> CreateReportSubscriptionRequest {
+        CreateReportSubscriptionRequest {
+            name: "Weekly sales".to_string(),
+            report: ReportKind::SalesSummary,
+            schedule: ReportSchedule::Weekly,
+            format: ReportFormat::Xlsx,
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            hour: 6,
+            weekday: 1,
+            day_of_month: 1,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_validate_subscription() {
+        assert!(validate_subscription(&request(&["cfo@example.com", "ops@example.com"])).is_ok());
+        assert!(validate_subscription(&request(&[])).is_err());
+        assert!(validate_subscription(&request(&["not-an-email"])).is_err());
+        assert!(validate_subscription(&request(&["cfo@example.com", "CFO@example.com"])).is_err());
+        assert!(validate_subscription(&CreateReportSubscriptionRequest {
+            hour: 24,
+            ..request(&["cfo@example.com"])
+        })
+        .is_err());
+        assert!(validate_subscription(&CreateReportSubscriptionRequest {
+            day_of_month: 31,
+            ..request(&["cfo@example.com"])
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_filename() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let run_at = at("2026-03-01T06:00:00Z");
+        assert_eq!(
+            filename(ReportKind::SalesSummary, ReportFormat::Xlsx, Some(period(ReportSchedule::Monthly, run_at)), run_at),
+            "sales-summary-2026-02-01-to-2026-02-28.xlsx"
+        );
+        assert_eq!(
+            filename(ReportKind::TaxLiability, ReportFormat::Csv, Some(period(ReportSchedule::Daily, run_at)), run_at),
+            "tax-liability-2026-02-28.csv"
+        );
+        assert_eq!(filename(ReportKind::LowStock, ReportFormat::Pdf, None, run_at), "low-stock-2026-03-01.pdf");
+    }
+
+    #[test]
+    fn test_run_report_summary() {
+        let report = ReportRunReport { subscriptions: 3, failed: 1, purged: 2 };
+        assert_eq!(report.to_string(), "sent 2 reports (1 failed), purged 2 expired files");
+    }
+}
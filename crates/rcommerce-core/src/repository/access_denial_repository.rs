@@ -0,0 +1,72 @@
+//! Access denial repository
+//!
+//! Recording and listing requests refused by the access controls.
+
+use async_trait::async_trait;
+
+use crate::{
+    Result, Error,
+    models::{AccessDenial, AccessDenialReason, NewAccessDenial},
+};
+
+/// Repository trait for the access denial audit log
+#[async_trait]
+pub trait AccessDenialRepository: Send + Sync {
+    /// Record a denial
+    async fn record(&self, denial: &NewAccessDenial) -> Result<()>;
+
+    /// Most recent denials first, optionally only for one reason
+    async fn list(&self, reason: Option<AccessDenialReason>, limit: i64, offset: i64) -> Result<Vec<AccessDenial>>;
+}
+
+/// PostgreSQL implementation of AccessDenialRepository
+pub struct PostgresAccessDenialRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAccessDenialRepository {
+    /// Create a new PostgreSQL access denial repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AccessDenialRepository for PostgresAccessDenialRepository {
+    async fn record(&self, denial: &NewAccessDenial) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO access_denials (reason, method, path, client_ip, api_key_id, customer_id, detail)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(denial.reason)
+        .bind(&denial.method)
+        .bind(&denial.path)
+        .bind(&denial.client_ip)
+        .bind(denial.api_key_id)
+        .bind(denial.customer_id)
+        .bind(&denial.detail)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record access denial: {}", e)))?;
+        Ok(())
+    }
+
+    async fn list(&self, reason: Option<AccessDenialReason>, limit: i64, offset: i64) -> Result<Vec<AccessDenial>> {
+        sqlx::query_as::<_, AccessDenial>(
+            r#"
+            SELECT * FROM access_denials
+            WHERE $1::access_denial_reason IS NULL OR reason = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(reason)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list access denials: {}", e)))
+    }
+}
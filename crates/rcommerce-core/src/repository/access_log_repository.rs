@@ -0,0 +1,110 @@
+//! Access log repository
+//!
+//! Batch inserts of logged requests, searching them and purging old ones.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    Result, Error,
+    models::{AccessLogEntry, AccessLogFilter, NewAccessLogEntry},
+};
+
+/// Repository trait for the access log
+#[async_trait]
+pub trait AccessLogRepository: Send + Sync {
+    /// Store a batch of entries
+    async fn insert_batch(&self, entries: &[NewAccessLogEntry]) -> Result<()>;
+
+    /// Matching entries, newest first
+    async fn search(&self, filter: &AccessLogFilter, limit: i64) -> Result<Vec<AccessLogEntry>>;
+
+    /// Delete entries logged before a time; returns how many
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of AccessLogRepository
+pub struct PostgresAccessLogRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAccessLogRepository {
+    /// Create a new PostgreSQL access log repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AccessLogRepository for PostgresAccessLogRepository {
+    async fn insert_batch(&self, entries: &[NewAccessLogEntry]) -> Result<()> {
+        let methods: Vec<&str> = entries.iter().map(|e| e.method.as_str()).collect();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        let statuses: Vec<i16> = entries.iter().map(|e| e.status as i16).collect();
+        let latencies: Vec<i32> = entries.iter().map(|e| e.latency_ms.min(i32::MAX as u32) as i32).collect();
+        let key_prefixes: Vec<Option<&str>> = entries.iter().map(|e| e.api_key_prefix.as_deref()).collect();
+        let networks: Vec<Option<&str>> = entries.iter().map(|e| e.client_network.as_deref()).collect();
+        let logged: Vec<_> = entries.iter().map(|e| e.created_at).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO access_log (method, path, status, latency_ms, api_key_prefix, client_network, created_at)
+            SELECT * FROM UNNEST(
+                $1::text[], $2::text[], $3::int2[], $4::int4[], $5::text[], $6::text[], $7::timestamptz[]
+            )
+            "#,
+        )
+        .bind(methods)
+        .bind(paths)
+        .bind(statuses)
+        .bind(latencies)
+        .bind(key_prefixes)
+        .bind(networks)
+        .bind(logged)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to write access log: {}", e)))?;
+        Ok(())
+    }
+
+    async fn search(&self, filter: &AccessLogFilter, limit: i64) -> Result<Vec<AccessLogEntry>> {
+        sqlx::query_as::<_, AccessLogEntry>(
+            r#"
+            SELECT * FROM access_log
+            WHERE ($1::text IS NULL OR api_key_prefix = $1)
+              AND ($2::text IS NULL OR starts_with(path, $2))
+              AND ($3::text IS NULL OR method = $3)
+              AND ($4::int2 IS NULL OR status = $4)
+              AND ($5::int2 IS NULL OR status >= $5)
+              AND ($6::int4 IS NULL OR latency_ms >= $6)
+              AND ($7::timestamptz IS NULL OR created_at >= $7)
+              AND ($8::timestamptz IS NULL OR created_at < $8)
+              AND ($9::int8 IS NULL OR id < $9)
+            ORDER BY id DESC
+            LIMIT $10
+            "#
+        )
+        .bind(&filter.api_key_prefix)
+        .bind(&filter.path)
+        .bind(filter.method.as_ref().map(|m| m.to_ascii_uppercase()))
+        .bind(filter.status)
+        .bind(filter.min_status)
+        .bind(filter.min_latency_ms)
+        .bind(filter.since)
+        .bind(filter.until)
+        .bind(filter.before_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to search access log: {}", e)))
+    }
+
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM access_log WHERE created_at < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge access log: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,415 @@
+//! Customer account repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{AccountOrder, AccountOrderLine, AccountShipment, CustomerNotificationPreferences, SavedPaymentMethod},
+    payment::agnostic::PaymentMethodToken,
+};
+
+/// Repository trait for what customers see and change of their own account
+#[async_trait]
+pub trait AccountRepository: Send + Sync {
+    /// The customer's orders, newest first
+    async fn orders(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AccountOrder>>;
+
+    /// An order, only if it belongs to the customer
+    async fn find_order(&self, customer_id: Uuid, order_id: Uuid) -> Result<Option<AccountOrder>>;
+
+    async fn order_lines(&self, order_id: Uuid) -> Result<Vec<AccountOrderLine>>;
+
+    async fn order_shipments(&self, order_id: Uuid) -> Result<Vec<AccountShipment>>;
+
+    /// Saved payment methods, the default first
+    async fn payment_methods(&self, customer_id: Uuid) -> Result<Vec<SavedPaymentMethod>>;
+
+    async fn find_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<Option<SavedPaymentMethod>>;
+
+    /// Record a tokenized payment method; the customer's first becomes the default
+    async fn save_payment_method(
+        &self,
+        customer_id: Uuid,
+        gateway: &str,
+        token: &PaymentMethodToken,
+        make_default: bool,
+    ) -> Result<SavedPaymentMethod>;
+
+    /// Forget a payment method; if it was the default, the newest left takes over
+    async fn delete_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<bool>;
+
+    async fn set_default_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<bool>;
+
+    /// The customer an email address or phone number belongs to; a live
+    /// account over a closed one
+    async fn customer_for_address(&self, address: &str) -> Result<Option<Uuid>>;
+
+    async fn notification_preferences(&self, customer_id: Uuid) -> Result<Option<CustomerNotificationPreferences>>;
+
+    async fn save_notification_preferences(
+        &self,
+        customer_id: Uuid,
+        preferences: &CustomerNotificationPreferences,
+    ) -> Result<()>;
+
+    /// Orders in progress and live subscriptions, which keep an account open
+    async fn open_commitments(&self, customer_id: Uuid) -> Result<(i64, i64)>;
+
+    /// Opt the customer out of all notifications, forget their payment
+    /// methods and soft delete them; false if already deleted
+    async fn close(&self, customer_id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of AccountRepository
+pub struct PostgresAccountRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAccountRepository {
+    /// Create a new PostgreSQL account repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+const ORDER_COLUMNS: &str = r#"
+    o.id, o.order_number, o.currency, o.subtotal, o.tax_total, o.shipping_total,
+    o.discount_total, o.total, o.status, o.payment_status, o.fulfillment_status,
+    (SELECT COALESCE(SUM(oi.quantity), 0) FROM order_items oi
+     WHERE oi.order_id = o.id AND NOT COALESCE(oi.is_bundle_component, false)) AS item_count,
+    o.created_at
+"#;
+
+#[async_trait]
+impl AccountRepository for PostgresAccountRepository {
+    async fn orders(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AccountOrder>> {
+        let query = format!(
+            "SELECT {} FROM orders o
+             WHERE o.customer_id = $1 AND o.draft = false AND o.deleted_at IS NULL
+             ORDER BY o.created_at DESC
+             LIMIT $2 OFFSET $3",
+            ORDER_COLUMNS
+        );
+        sqlx::query_as::<_, AccountOrder>(&query)
+            .bind(customer_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list orders: {}", e)))
+    }
+
+    async fn find_order(&self, customer_id: Uuid, order_id: Uuid) -> Result<Option<AccountOrder>> {
+        let query = format!(
+            "SELECT {} FROM orders o
+             WHERE o.id = $1 AND o.customer_id = $2 AND o.draft = false AND o.deleted_at IS NULL",
+            ORDER_COLUMNS
+        );
+        sqlx::query_as::<_, AccountOrder>(&query)
+            .bind(order_id)
+            .bind(customer_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get order: {}", e)))
+    }
+
+    async fn order_lines(&self, order_id: Uuid) -> Result<Vec<AccountOrderLine>> {
+        sqlx::query_as::<_, AccountOrderLine>(
+            r#"
+            SELECT oi.id, oi.product_id, oi.variant_id, oi.title, oi.variant_title, oi.sku, oi.quantity,
+                   oi.price, oi.total, oi.image_url, COALESCE(p.is_active, false) AS available,
+                   COALESCE(oi.is_bundle_component, false) AS is_bundle_component
+            FROM order_items oi
+            LEFT JOIN products p ON p.id = oi.product_id AND p.deleted_at IS NULL
+            WHERE oi.order_id = $1
+            ORDER BY oi.created_at, oi.id
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order items: {}", e)))
+    }
+
+    async fn order_shipments(&self, order_id: Uuid) -> Result<Vec<AccountShipment>> {
+        sqlx::query_as::<_, AccountShipment>(
+            r#"
+            SELECT id, status, tracking_company, tracking_number, tracking_url, tracking_status,
+                   tracking_detail, shipped_at, delivered_at
+            FROM fulfillments
+            WHERE order_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipments: {}", e)))
+    }
+
+    async fn payment_methods(&self, customer_id: Uuid) -> Result<Vec<SavedPaymentMethod>> {
+        sqlx::query_as::<_, SavedPaymentMethod>(
+            "SELECT * FROM customer_payment_methods WHERE customer_id = $1 ORDER BY is_default DESC, created_at DESC",
+        )
+        .bind(customer_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list payment methods: {}", e)))
+    }
+
+    async fn find_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<Option<SavedPaymentMethod>> {
+        sqlx::query_as::<_, SavedPaymentMethod>(
+            "SELECT * FROM customer_payment_methods WHERE id = $1 AND customer_id = $2",
+        )
+        .bind(id)
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get payment method: {}", e)))
+    }
+
+    async fn save_payment_method(
+        &self,
+        customer_id: Uuid,
+        gateway: &str,
+        token: &PaymentMethodToken,
+        make_default: bool,
+    ) -> Result<SavedPaymentMethod> {
+        let info = &token.payment_method;
+        let method_type = serde_json::to_value(&info.method_type)?
+            .as_str()
+            .unwrap_or("card")
+            .to_string();
+
+        let mut tx = self.db.begin().await.map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        let has_default: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM customer_payment_methods WHERE customer_id = $1 AND is_default)",
+        )
+        .bind(customer_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save payment method: {}", e)))?;
+        if make_default && has_default {
+            sqlx::query("UPDATE customer_payment_methods SET is_default = false WHERE customer_id = $1 AND is_default")
+                .bind(customer_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to save payment method: {}", e)))?;
+        }
+        let method = sqlx::query_as::<_, SavedPaymentMethod>(
+            r#"
+            INSERT INTO customer_payment_methods
+                (customer_id, gateway, token, method_type, card_brand, last_four, exp_month, exp_year,
+                 cardholder_name, is_default)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#,
+        )
+        .bind(customer_id)
+        .bind(gateway)
+        .bind(&token.token)
+        .bind(method_type)
+        .bind(&info.card_brand)
+        .bind(&info.last_four)
+        .bind(&info.exp_month)
+        .bind(&info.exp_year)
+        .bind(&info.cardholder_name)
+        .bind(make_default || !has_default)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save payment method: {}", e)))?;
+        tx.commit().await.map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(method)
+    }
+
+    async fn delete_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<bool> {
+        let mut tx = self.db.begin().await.map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        let deleted: Option<bool> = sqlx::query_scalar(
+            "DELETE FROM customer_payment_methods WHERE id = $1 AND customer_id = $2 RETURNING is_default",
+        )
+        .bind(id)
+        .bind(customer_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to delete payment method: {}", e)))?;
+        if deleted == Some(true) {
+            sqlx::query(
+                r#"
+                UPDATE customer_payment_methods SET is_default = true
+                WHERE id = (
+                    SELECT id FROM customer_payment_methods WHERE customer_id = $1
+                    ORDER BY created_at DESC LIMIT 1
+                )
+                "#,
+            )
+            .bind(customer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete payment method: {}", e)))?;
+        }
+        tx.commit().await.map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(deleted.is_some())
+    }
+
+    async fn set_default_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<bool> {
+        let mut tx = self.db.begin().await.map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        sqlx::query(
+            "UPDATE customer_payment_methods SET is_default = false WHERE customer_id = $1 AND is_default AND id <> $2",
+        )
+        .bind(customer_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to set default payment method: {}", e)))?;
+        let result = sqlx::query("UPDATE customer_payment_methods SET is_default = true WHERE id = $1 AND customer_id = $2")
+            .bind(id)
+            .bind(customer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to set default payment method: {}", e)))?;
+        if result.rows_affected() == 0 {
+            // Not theirs: dropping the transaction leaves the current default alone
+            return Ok(false);
+        }
+        tx.commit().await.map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(true)
+    }
+
+    async fn customer_for_address(&self, address: &str) -> Result<Option<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM customers
+            WHERE email = $1 OR phone = $1
+            ORDER BY deleted_at IS NULL DESC, created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(address)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to find customer: {}", e)))
+    }
+
+    async fn notification_preferences(&self, customer_id: Uuid) -> Result<Option<CustomerNotificationPreferences>> {
+        sqlx::query_as::<_, CustomerNotificationPreferences>(
+            r#"
+            SELECT email_enabled, sms_enabled, push_enabled, marketing_emails, order_updates,
+                   shipping_updates, quiet_hours_start, quiet_hours_end
+            FROM customer_notification_preferences
+            WHERE customer_id = $1
+            "#,
+        )
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get notification preferences: {}", e)))
+    }
+
+    async fn save_notification_preferences(
+        &self,
+        customer_id: Uuid,
+        preferences: &CustomerNotificationPreferences,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO customer_notification_preferences
+                (customer_id, email_enabled, sms_enabled, push_enabled, marketing_emails, order_updates,
+                 shipping_updates, quiet_hours_start, quiet_hours_end, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+            ON CONFLICT (customer_id) DO UPDATE SET
+                email_enabled = EXCLUDED.email_enabled,
+                sms_enabled = EXCLUDED.sms_enabled,
+                push_enabled = EXCLUDED.push_enabled,
+                marketing_emails = EXCLUDED.marketing_emails,
+                order_updates = EXCLUDED.order_updates,
+                shipping_updates = EXCLUDED.shipping_updates,
+                quiet_hours_start = EXCLUDED.quiet_hours_start,
+                quiet_hours_end = EXCLUDED.quiet_hours_end,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(customer_id)
+        .bind(preferences.email_enabled)
+        .bind(preferences.sms_enabled)
+        .bind(preferences.push_enabled)
+        .bind(preferences.marketing_emails)
+        .bind(preferences.order_updates)
+        .bind(preferences.shipping_updates)
+        .bind(preferences.quiet_hours_start)
+        .bind(preferences.quiet_hours_end)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save notification preferences: {}", e)))?;
+
+        // Keep the customer record's marketing consent in step
+        sqlx::query(
+            "UPDATE customers SET accepts_marketing = $2, marketing_opt_in = $2, email_notifications = $3, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(customer_id)
+        .bind(preferences.marketing_emails)
+        .bind(preferences.email_enabled)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save notification preferences: {}", e)))?;
+        Ok(())
+    }
+
+    async fn open_commitments(&self, customer_id: Uuid) -> Result<(i64, i64)> {
+        sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM orders
+                 WHERE customer_id = $1 AND draft = false AND deleted_at IS NULL
+                   AND status IN ('pending', 'confirmed', 'processing', 'on_hold')),
+                (SELECT COUNT(*) FROM subscriptions
+                 WHERE customer_id = $1 AND status IN ('active', 'paused', 'past_due', 'trialing', 'pending'))
+            "#,
+        )
+        .bind(customer_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to check open orders: {}", e)))
+    }
+
+    async fn close(&self, customer_id: Uuid) -> Result<bool> {
+        let mut tx = self.db.begin().await.map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        let result = sqlx::query(
+            r#"
+            UPDATE customers
+            SET accepts_marketing = false, marketing_opt_in = false, email_notifications = false,
+                deleted_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to close account: {}", e)))?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query("DELETE FROM customer_payment_methods WHERE customer_id = $1")
+            .bind(customer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to close account: {}", e)))?;
+        sqlx::query(
+            r#"
+            INSERT INTO customer_notification_preferences
+                (customer_id, email_enabled, sms_enabled, push_enabled, marketing_emails, order_updates, shipping_updates)
+            VALUES ($1, false, false, false, false, false, false)
+            ON CONFLICT (customer_id) DO UPDATE SET
+                email_enabled = false, sms_enabled = false, push_enabled = false, marketing_emails = false,
+                order_updates = false, shipping_updates = false, updated_at = NOW()
+            "#,
+        )
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to close account: {}", e)))?;
+        tx.commit().await.map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(true)
+    }
+}
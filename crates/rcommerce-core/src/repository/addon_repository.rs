@@ -0,0 +1,286 @@
+//! Order add-on repository
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        AddCartAddonRequest, Addon, CartAddon, CreateAddonRequest, OrderAddon, PickItem, UpdateAddonRequest,
+    },
+};
+
+const CART_ADDON_SELECT: &str = r#"
+    SELECT ca.id, ca.cart_id, ca.cart_item_id, ca.addon_id, a.code, a.name, a.scope,
+           a.price AS unit_price, a.per_unit, a.tax_category_id, ca.message, a.fulfillment_instructions,
+           ci.quantity AS item_quantity, ci.product_id, ci.variant_id
+    FROM cart_addons ca
+    JOIN addons a ON a.id = ca.addon_id
+    LEFT JOIN cart_items ci ON ci.id = ca.cart_item_id
+"#;
+
+/// Repository trait for order add-ons
+#[async_trait]
+pub trait AddonRepository: Send + Sync {
+    /// Create an add-on
+    async fn create(&self, request: &CreateAddonRequest) -> Result<Addon>;
+
+    /// Get an add-on
+    async fn find(&self, id: Uuid) -> Result<Option<Addon>>;
+
+    /// List add-ons by sort order
+    async fn list(&self, active_only: bool) -> Result<Vec<Addon>>;
+
+    /// Update an add-on
+    async fn update(&self, id: Uuid, request: &UpdateAddonRequest) -> Result<Option<Addon>>;
+
+    /// Quantity of a cart line, if the line is in the cart
+    async fn cart_item_quantity(&self, cart_id: Uuid, cart_item_id: Uuid) -> Result<Option<i32>>;
+
+    /// Active add-ons chosen on a cart
+    async fn cart_addons(&self, cart_id: Uuid) -> Result<Vec<CartAddon>>;
+
+    /// Add an add-on to a cart, replacing the message if it is already there
+    async fn add_to_cart(&self, cart_id: Uuid, request: &AddCartAddonRequest) -> Result<()>;
+
+    /// Remove an add-on from a cart; false if it was not there
+    async fn remove_from_cart(&self, cart_id: Uuid, cart_addon_id: Uuid) -> Result<bool>;
+
+    /// Record a cart's add-ons (with their tax) on the order created from it
+    async fn attach_to_order(&self, order_id: Uuid, addons: &[(CartAddon, Decimal)]) -> Result<Vec<OrderAddon>>;
+
+    /// Add-ons sold on an order
+    async fn order_addons(&self, order_id: Uuid) -> Result<Vec<OrderAddon>>;
+
+    /// An order's number, if the order exists
+    async fn order_number(&self, order_id: Uuid) -> Result<Option<String>>;
+
+    /// An order's lines to pick
+    async fn pick_items(&self, order_id: Uuid) -> Result<Vec<PickItem>>;
+}
+
+/// PostgreSQL implementation of AddonRepository
+pub struct PostgresAddonRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAddonRepository {
+    /// Create a new PostgreSQL add-on repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AddonRepository for PostgresAddonRepository {
+    async fn create(&self, request: &CreateAddonRequest) -> Result<Addon> {
+        sqlx::query_as::<_, Addon>(
+            r#"
+            INSERT INTO addons (
+                code, name, description, scope, price, per_unit, tax_category_id,
+                accepts_message, max_message_length, fulfillment_instructions, sort_order
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, COALESCE($9, 250), $10, $11)
+            RETURNING *
+            "#
+        )
+        .bind(&request.code)
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(request.scope)
+        .bind(request.price)
+        .bind(request.per_unit)
+        .bind(request.tax_category_id)
+        .bind(request.accepts_message)
+        .bind(request.max_message_length)
+        .bind(&request.fulfillment_instructions)
+        .bind(request.sort_order)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create add-on: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<Addon>> {
+        sqlx::query_as::<_, Addon>("SELECT * FROM addons WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get add-on: {}", e)))
+    }
+
+    async fn list(&self, active_only: bool) -> Result<Vec<Addon>> {
+        sqlx::query_as::<_, Addon>(
+            "SELECT * FROM addons WHERE is_active OR NOT $1 ORDER BY sort_order, name"
+        )
+        .bind(active_only)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list add-ons: {}", e)))
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateAddonRequest) -> Result<Option<Addon>> {
+        sqlx::query_as::<_, Addon>(
+            r#"
+            UPDATE addons
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                price = COALESCE($4, price),
+                per_unit = COALESCE($5, per_unit),
+                tax_category_id = COALESCE($6, tax_category_id),
+                accepts_message = COALESCE($7, accepts_message),
+                max_message_length = COALESCE($8, max_message_length),
+                fulfillment_instructions = COALESCE($9, fulfillment_instructions),
+                is_active = COALESCE($10, is_active),
+                sort_order = COALESCE($11, sort_order)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(request.price)
+        .bind(request.per_unit)
+        .bind(request.tax_category_id)
+        .bind(request.accepts_message)
+        .bind(request.max_message_length)
+        .bind(&request.fulfillment_instructions)
+        .bind(request.is_active)
+        .bind(request.sort_order)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update add-on: {}", e)))
+    }
+
+    async fn cart_item_quantity(&self, cart_id: Uuid, cart_item_id: Uuid) -> Result<Option<i32>> {
+        sqlx::query_scalar::<_, i32>("SELECT quantity FROM cart_items WHERE id = $1 AND cart_id = $2")
+            .bind(cart_item_id)
+            .bind(cart_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get cart item: {}", e)))
+    }
+
+    async fn cart_addons(&self, cart_id: Uuid) -> Result<Vec<CartAddon>> {
+        sqlx::query_as::<_, CartAddon>(&format!(
+            "{} WHERE ca.cart_id = $1 AND a.is_active ORDER BY a.sort_order, ca.created_at",
+            CART_ADDON_SELECT
+        ))
+        .bind(cart_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list cart add-ons: {}", e)))
+    }
+
+    async fn add_to_cart(&self, cart_id: Uuid, request: &AddCartAddonRequest) -> Result<()> {
+        let conflict = if request.cart_item_id.is_some() {
+            "(cart_item_id, addon_id) WHERE cart_item_id IS NOT NULL"
+        } else {
+            "(cart_id, addon_id) WHERE cart_item_id IS NULL"
+        };
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO cart_addons (cart_id, cart_item_id, addon_id, message)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT {} DO UPDATE SET message = EXCLUDED.message
+            "#,
+            conflict
+        ))
+        .bind(cart_id)
+        .bind(request.cart_item_id)
+        .bind(request.addon_id)
+        .bind(&request.message)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to add cart add-on: {}", e)))?;
+        Ok(())
+    }
+
+    async fn remove_from_cart(&self, cart_id: Uuid, cart_addon_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM cart_addons WHERE id = $1 AND cart_id = $2")
+            .bind(cart_addon_id)
+            .bind(cart_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to remove cart add-on: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn attach_to_order(&self, order_id: Uuid, addons: &[(CartAddon, Decimal)]) -> Result<Vec<OrderAddon>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let mut attached = Vec::with_capacity(addons.len());
+        for (addon, tax_amount) in addons {
+            // Item add-ons follow their cart line to the order line for the same product
+            let order_addon = sqlx::query_as::<_, OrderAddon>(
+                r#"
+                INSERT INTO order_addons (
+                    order_id, order_item_id, addon_id, code, name, quantity, unit_price, total,
+                    tax_amount, message, fulfillment_instructions
+                )
+                VALUES (
+                    $1,
+                    (SELECT id FROM order_items
+                     WHERE order_id = $1 AND product_id = $2 AND variant_id IS NOT DISTINCT FROM $3
+                     ORDER BY created_at LIMIT 1),
+                    $4, $5, $6, $7, $8, $9, $10, $11, $12
+                )
+                RETURNING *
+                "#
+            )
+            .bind(order_id)
+            .bind(addon.product_id)
+            .bind(addon.variant_id)
+            .bind(addon.addon_id)
+            .bind(&addon.code)
+            .bind(&addon.name)
+            .bind(addon.quantity())
+            .bind(addon.unit_price)
+            .bind(addon.total())
+            .bind(tax_amount)
+            .bind(&addon.message)
+            .bind(&addon.fulfillment_instructions)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record order add-on: {}", e)))?;
+            attached.push(order_addon);
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit order add-ons: {}", e)))?;
+        Ok(attached)
+    }
+
+    async fn order_addons(&self, order_id: Uuid) -> Result<Vec<OrderAddon>> {
+        sqlx::query_as::<_, OrderAddon>("SELECT * FROM order_addons WHERE order_id = $1 ORDER BY created_at")
+            .bind(order_id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list order add-ons: {}", e)))
+    }
+
+    async fn order_number(&self, order_id: Uuid) -> Result<Option<String>> {
+        sqlx::query_scalar::<_, String>("SELECT order_number FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get order: {}", e)))
+    }
+
+    async fn pick_items(&self, order_id: Uuid) -> Result<Vec<PickItem>> {
+        sqlx::query_as::<_, PickItem>(
+            r#"
+            SELECT id, sku, title, variant_title, quantity
+            FROM order_items
+            WHERE order_id = $1 AND requires_shipping
+            ORDER BY created_at
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list order items: {}", e)))
+    }
+}
@@ -0,0 +1,229 @@
+//! Customer address book repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{CreateAddressRequest, CustomerAddress, CUSTOMER_ADDRESS_COLUMNS},
+};
+
+/// Outcome of checking an address with a shipping carrier
+#[derive(Debug, Clone, Default)]
+pub struct AddressCheck {
+    /// Carrier that validated the address; None if it was not checked
+    pub provider: Option<String>,
+    pub residential: Option<bool>,
+    pub validated_at: Option<DateTime<Utc>>,
+}
+
+/// Repository trait for customer address books
+#[async_trait]
+pub trait AddressRepository: Send + Sync {
+    /// Whether the customer exists
+    async fn customer_exists(&self, customer_id: Uuid) -> Result<bool>;
+
+    /// A customer's addresses, defaults first
+    async fn list(&self, customer_id: Uuid) -> Result<Vec<CustomerAddress>>;
+
+    /// Get one of a customer's addresses
+    async fn find(&self, customer_id: Uuid, id: Uuid) -> Result<Option<CustomerAddress>>;
+
+    /// Add an address; making it a default clears the customer's previous one
+    async fn create(&self, customer_id: Uuid, request: &CreateAddressRequest, check: &AddressCheck) -> Result<CustomerAddress>;
+
+    /// Replace an address; making it a default clears the customer's previous one
+    async fn update(
+        &self,
+        customer_id: Uuid,
+        id: Uuid,
+        request: &CreateAddressRequest,
+        check: &AddressCheck,
+    ) -> Result<Option<CustomerAddress>>;
+
+    /// Delete an address; false if it was not there
+    async fn delete(&self, customer_id: Uuid, id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of AddressRepository
+pub struct PostgresAddressRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAddressRepository {
+    /// Create a new PostgreSQL address repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// Clear the customer's other defaults before `keep` becomes one
+async fn clear_defaults(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    customer_id: Uuid,
+    keep: Option<Uuid>,
+    request: &CreateAddressRequest,
+) -> Result<()> {
+    if !request.is_default_shipping && !request.is_default_billing {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE addresses
+        SET is_default_shipping = is_default_shipping AND NOT $3,
+            is_default_billing = is_default_billing AND NOT $4
+        WHERE customer_id = $1 AND id IS DISTINCT FROM $2
+          AND ((is_default_shipping AND $3) OR (is_default_billing AND $4))
+        "#
+    )
+    .bind(customer_id)
+    .bind(keep)
+    .bind(request.is_default_shipping)
+    .bind(request.is_default_billing)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to clear default addresses: {}", e)))?;
+    Ok(())
+}
+
+#[async_trait]
+impl AddressRepository for PostgresAddressRepository {
+    async fn customer_exists(&self, customer_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM customers WHERE id = $1)")
+            .bind(customer_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to check customer: {}", e)))
+    }
+
+    async fn list(&self, customer_id: Uuid) -> Result<Vec<CustomerAddress>> {
+        sqlx::query_as::<_, CustomerAddress>(&format!(
+            "SELECT {} FROM addresses WHERE customer_id = $1 \
+             ORDER BY is_default_shipping DESC, is_default_billing DESC, created_at",
+            CUSTOMER_ADDRESS_COLUMNS
+        ))
+        .bind(customer_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list addresses: {}", e)))
+    }
+
+    async fn find(&self, customer_id: Uuid, id: Uuid) -> Result<Option<CustomerAddress>> {
+        sqlx::query_as::<_, CustomerAddress>(&format!(
+            "SELECT {} FROM addresses WHERE id = $1 AND customer_id = $2",
+            CUSTOMER_ADDRESS_COLUMNS
+        ))
+        .bind(id)
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get address: {}", e)))
+    }
+
+    async fn create(&self, customer_id: Uuid, request: &CreateAddressRequest, check: &AddressCheck) -> Result<CustomerAddress> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        clear_defaults(&mut tx, customer_id, None, request).await?;
+
+        let address = sqlx::query_as::<_, CustomerAddress>(&format!(
+            r#"
+            INSERT INTO addresses (
+                customer_id, label, first_name, last_name, company, phone, address1, address2,
+                city, province, country, zip, is_default_shipping, is_default_billing,
+                residential, validation_provider, validated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            RETURNING {}
+            "#,
+            CUSTOMER_ADDRESS_COLUMNS
+        ))
+        .bind(customer_id)
+        .bind(&request.label)
+        .bind(&request.first_name)
+        .bind(&request.last_name)
+        .bind(&request.company)
+        .bind(&request.phone)
+        .bind(&request.address1)
+        .bind(&request.address2)
+        .bind(&request.city)
+        .bind(&request.state)
+        .bind(&request.country)
+        .bind(&request.zip)
+        .bind(request.is_default_shipping)
+        .bind(request.is_default_billing)
+        .bind(check.residential)
+        .bind(&check.provider)
+        .bind(check.validated_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create address: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit address: {}", e)))?;
+        Ok(address)
+    }
+
+    async fn update(
+        &self,
+        customer_id: Uuid,
+        id: Uuid,
+        request: &CreateAddressRequest,
+        check: &AddressCheck,
+    ) -> Result<Option<CustomerAddress>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        clear_defaults(&mut tx, customer_id, Some(id), request).await?;
+
+        let address = sqlx::query_as::<_, CustomerAddress>(&format!(
+            r#"
+            UPDATE addresses
+            SET label = $3, first_name = $4, last_name = $5, company = $6, phone = $7,
+                address1 = $8, address2 = $9, city = $10, province = $11, country = $12, zip = $13,
+                is_default_shipping = $14, is_default_billing = $15,
+                residential = $16, validation_provider = $17, validated_at = $18
+            WHERE id = $1 AND customer_id = $2
+            RETURNING {}
+            "#,
+            CUSTOMER_ADDRESS_COLUMNS
+        ))
+        .bind(id)
+        .bind(customer_id)
+        .bind(&request.label)
+        .bind(&request.first_name)
+        .bind(&request.last_name)
+        .bind(&request.company)
+        .bind(&request.phone)
+        .bind(&request.address1)
+        .bind(&request.address2)
+        .bind(&request.city)
+        .bind(&request.state)
+        .bind(&request.country)
+        .bind(&request.zip)
+        .bind(request.is_default_shipping)
+        .bind(request.is_default_billing)
+        .bind(check.residential)
+        .bind(&check.provider)
+        .bind(check.validated_at)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update address: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit address: {}", e)))?;
+        Ok(address)
+    }
+
+    async fn delete(&self, customer_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM addresses WHERE id = $1 AND customer_id = $2")
+            .bind(id)
+            .bind(customer_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete address: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,98 @@
+//! Storefront analytics repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::analytics::{NewStorefrontEvent, StorefrontEventKind};
+use crate::{Error, Result};
+
+/// Repository trait for storefront funnel events
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync {
+    async fn insert_events(&self, events: &[NewStorefrontEvent]) -> Result<()>;
+
+    /// Estimated sessions with each kind of event between two times
+    /// (sessions weighted by the sample rate their events were kept at)
+    async fn funnel_sessions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<(StorefrontEventKind, f64)>>;
+
+    /// Drop events from before a time; returns how many
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of AnalyticsRepository
+#[derive(Clone)]
+pub struct PostgresAnalyticsRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAnalyticsRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AnalyticsRepository for PostgresAnalyticsRepository {
+    async fn insert_events(&self, events: &[NewStorefrontEvent]) -> Result<()> {
+        let kinds: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        let sessions: Vec<&str> = events.iter().map(|e| e.session_hash.as_str()).collect();
+        let products: Vec<_> = events.iter().map(|e| e.product_id).collect();
+        let variants: Vec<_> = events.iter().map(|e| e.variant_id).collect();
+        let carts: Vec<_> = events.iter().map(|e| e.cart_id).collect();
+        let quantities: Vec<_> = events.iter().map(|e| e.quantity).collect();
+        let weights: Vec<f64> = events.iter().map(|e| e.weight).collect();
+        let occurred: Vec<_> = events.iter().map(|e| e.occurred_at).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO storefront_events
+            (event, session_hash, product_id, variant_id, cart_id, quantity, weight, occurred_at)
+            SELECT * FROM UNNEST(
+                $1::storefront_event_kind[], $2::text[], $3::uuid[], $4::uuid[], $5::uuid[],
+                $6::int[], $7::float8[], $8::timestamptz[]
+            )
+            "#,
+        )
+        .bind(kinds)
+        .bind(sessions)
+        .bind(products)
+        .bind(variants)
+        .bind(carts)
+        .bind(quantities)
+        .bind(weights)
+        .bind(occurred)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to store storefront events: {}", e)))?;
+        Ok(())
+    }
+
+    async fn funnel_sessions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<(StorefrontEventKind, f64)>> {
+        sqlx::query_as::<_, (StorefrontEventKind, f64)>(
+            r#"
+            SELECT event, SUM(weight)::float8
+            FROM (
+                SELECT event, session_hash, MAX(weight) AS weight
+                FROM storefront_events
+                WHERE occurred_at >= $1 AND occurred_at < $2
+                GROUP BY event, session_hash
+            ) sessions
+            GROUP BY event
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to count funnel sessions: {}", e)))
+    }
+
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM storefront_events WHERE occurred_at < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge storefront events: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
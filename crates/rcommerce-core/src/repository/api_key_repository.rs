@@ -4,15 +4,39 @@
 //! - Finding API keys by prefix
 //! - Validating API keys
 //! - Updating last used timestamp
-//! - Listing, creating, revoking API keys
+//! - Listing, creating, revoking and rotating API keys
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use std::net::IpAddr;
 use uuid::Uuid;
 
 use crate::{Result, Error};
 
+/// Kind of API key
+///
+/// Secret keys authenticate server-to-server calls and must never reach a
+/// browser. Publishable keys are safe to embed in storefront JavaScript:
+/// they only open the read-only storefront endpoints, from their allowed
+/// origins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(type_name = "api_key_type", rename_all = "snake_case")]
+pub enum ApiKeyType {
+    #[default]
+    Secret,
+    Publishable,
+}
+
+impl ApiKeyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyType::Secret => "secret",
+            ApiKeyType::Publishable => "publishable",
+        }
+    }
+}
+
 /// API Key record from database
 #[derive(Debug, Clone, FromRow)]
 pub struct ApiKeyRecord {
@@ -31,6 +55,116 @@ pub struct ApiKeyRecord {
     pub updated_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub revoked_reason: Option<String>,
+    pub key_type: ApiKeyType,
+    pub allowed_origins: Vec<String>,
+    pub rotated_to: Option<Uuid>,
+    pub allowed_ips: Vec<String>,
+}
+
+impl ApiKeyRecord {
+    pub fn is_publishable(&self) -> bool {
+        self.key_type == ApiKeyType::Publishable
+    }
+
+    /// Whether a request from `origin` (e.g. `https://shop.example.com`) may
+    /// use this key. Keys without allowed origins match nothing.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|pattern| origin_matches(pattern, origin))
+    }
+
+    /// Whether a request from `ip` may use this key. Keys without an IP
+    /// allowlist can be used from anywhere.
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        self.allowed_ips.is_empty() || self.allowed_ips.iter().any(|network| ip_matches(network, ip))
+    }
+}
+
+/// Match an address against an allowlist entry: a single address
+/// (`203.0.113.7`) or a CIDR network (`10.0.0.0/8`, `2001:db8::/32`).
+/// IPv4-mapped IPv6 addresses match IPv4 entries. Malformed entries match
+/// nothing.
+pub fn ip_matches(network: &str, ip: IpAddr) -> bool {
+    let network = network.trim();
+    let (addr, prefix) = match network.split_once('/') {
+        Some((addr, prefix)) => match prefix.parse::<u8>() {
+            Ok(prefix) => (addr, Some(prefix)),
+            Err(_) => return false,
+        },
+        None => (network, None),
+    };
+    let Ok(addr) = addr.parse::<IpAddr>() else {
+        return false;
+    };
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+
+    match (addr, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let prefix = prefix.unwrap_or(32);
+            prefix <= 32 && prefix_matches(u32::from(net).into(), u32::from(ip).into(), 32, prefix)
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let prefix = prefix.unwrap_or(128);
+            prefix <= 128 && prefix_matches(u128::from(net), u128::from(ip), 128, prefix)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `network` and `ip` (each `bits` wide) share their first `prefix` bits
+fn prefix_matches(network: u128, ip: u128, bits: u8, prefix: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = u32::from(bits - prefix);
+    (network >> shift) == (ip >> shift)
+}
+
+/// Whether `entry` is a usable IP allowlist entry
+pub fn is_valid_ip_network(entry: &str) -> bool {
+    let entry = entry.trim();
+    let (addr, max) = match entry.split('/').next().and_then(|a| a.parse::<IpAddr>().ok()) {
+        Some(IpAddr::V4(_)) => (true, 32),
+        Some(IpAddr::V6(_)) => (true, 128),
+        None => (false, 0),
+    };
+    addr && entry
+        .split_once('/')
+        .map_or(true, |(_, prefix)| prefix.parse::<u8>().is_ok_and(|p| p <= max))
+}
+
+/// Match a request origin against an allowed-origin pattern
+///
+/// `https://shop.example.com` must match scheme, host and port exactly,
+/// `shop.example.com` matches any scheme and port, `shop.example.com:8080`
+/// any scheme, and `*.example.com` any subdomain (not the bare domain).
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('/').to_ascii_lowercase();
+    let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    if authority.is_empty() {
+        return false;
+    }
+
+    if pattern.contains("://") {
+        return pattern == origin;
+    }
+
+    let subject = if pattern.contains(':') {
+        authority
+    } else {
+        authority.split(':').next().unwrap_or(authority)
+    };
+    match pattern.strip_prefix("*.") {
+        Some(domain) => subject
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => subject == pattern,
+    }
 }
 
 /// API Key repository trait for database operations
@@ -62,6 +196,19 @@ pub trait ApiKeyRepository: Send + Sync + 'static {
     
     /// Verify if an API key is valid and return its record
     async fn verify_key(&self, full_key: &str) -> Result<Option<ApiKeyRecord>>;
+
+    /// Replace an active key with a new one carrying the same settings
+    ///
+    /// The old key keeps working until `grace` has passed (or its own
+    /// expiry, if sooner) and points at its successor. Returns None if no
+    /// active key has `prefix`.
+    async fn rotate(
+        &self,
+        prefix: &str,
+        new_prefix: &str,
+        new_hash: &str,
+        grace: chrono::Duration,
+    ) -> Result<Option<ApiKeyRecord>>;
 }
 
 /// Request to create a new API key
@@ -74,6 +221,9 @@ pub struct CreateApiKeyRequest {
     pub scopes: Vec<String>,
     pub expires_at: Option<DateTime<Utc>>,
     pub rate_limit_per_minute: Option<i32>,
+    pub key_type: ApiKeyType,
+    pub allowed_origins: Vec<String>,
+    pub allowed_ips: Vec<String>,
 }
 
 /// PostgreSQL implementation of API key repository
@@ -175,8 +325,8 @@ impl ApiKeyRepository for PostgresApiKeyRepository {
             r#"
             INSERT INTO api_keys (
                 customer_id, key_prefix, key_hash, name, scopes, 
-                expires_at, rate_limit_per_minute
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                expires_at, rate_limit_per_minute, key_type, allowed_origins, allowed_ips
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#
         )
@@ -187,6 +337,9 @@ impl ApiKeyRepository for PostgresApiKeyRepository {
         .bind(request.scopes)
         .bind(request.expires_at)
         .bind(request.rate_limit_per_minute)
+        .bind(request.key_type)
+        .bind(request.allowed_origins)
+        .bind(request.allowed_ips)
         .fetch_one(&self.pool)
         .await
         .map_err(Error::Database)?;
@@ -256,6 +409,80 @@ impl ApiKeyRepository for PostgresApiKeyRepository {
             None => Ok(None),
         }
     }
+
+    async fn rotate(
+        &self,
+        prefix: &str,
+        new_prefix: &str,
+        new_hash: &str,
+        grace: chrono::Duration,
+    ) -> Result<Option<ApiKeyRecord>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let old = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            SELECT * FROM api_keys
+            WHERE key_prefix = $1
+            AND is_active = true
+            AND revoked_at IS NULL
+            AND (expires_at IS NULL OR expires_at > NOW())
+            FOR UPDATE
+            "#
+        )
+        .bind(prefix)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let Some(old) = old else {
+            return Ok(None);
+        };
+
+        let successor = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            INSERT INTO api_keys (
+                customer_id, key_prefix, key_hash, name, scopes,
+                expires_at, rate_limit_per_minute, key_type, allowed_origins, allowed_ips
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING *
+            "#
+        )
+        .bind(old.customer_id)
+        .bind(new_prefix)
+        .bind(new_hash)
+        .bind(&old.name)
+        .bind(&old.scopes)
+        .bind(old.expires_at)
+        .bind(old.rate_limit_per_minute)
+        .bind(old.key_type)
+        .bind(&old.allowed_origins)
+        .bind(&old.allowed_ips)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let grace_ends = Utc::now() + grace;
+        let expires_at = match old.expires_at {
+            Some(expires_at) if expires_at < grace_ends => expires_at,
+            _ => grace_ends,
+        };
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET expires_at = $1, rotated_to = $2, updated_at = NOW()
+            WHERE id = $3
+            "#
+        )
+        .bind(expires_at)
+        .bind(successor.id)
+        .bind(old.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+        Ok(Some(successor))
+    }
 }
 
 /// In-memory API key repository for testing
@@ -332,6 +559,10 @@ pub mod mock {
                 updated_at: Utc::now(),
                 revoked_at: None,
                 revoked_reason: None,
+                key_type: request.key_type,
+                allowed_origins: request.allowed_origins,
+                rotated_to: None,
+                allowed_ips: request.allowed_ips,
             };
             keys.insert(request.key_prefix, record.clone());
             Ok(record)
@@ -361,6 +592,38 @@ pub mod mock {
             }
             self.find_active_by_prefix(parts[0]).await
         }
+
+        async fn rotate(
+            &self,
+            prefix: &str,
+            new_prefix: &str,
+            new_hash: &str,
+            grace: chrono::Duration,
+        ) -> Result<Option<ApiKeyRecord>> {
+            let Some(old) = self.find_active_by_prefix(prefix).await? else {
+                return Ok(None);
+            };
+            let successor = self.create(CreateApiKeyRequest {
+                customer_id: old.customer_id,
+                key_prefix: new_prefix.to_string(),
+                key_hash: new_hash.to_string(),
+                name: old.name.clone(),
+                scopes: old.scopes.clone(),
+                expires_at: old.expires_at,
+                rate_limit_per_minute: old.rate_limit_per_minute,
+                key_type: old.key_type,
+                allowed_origins: old.allowed_origins.clone(),
+                allowed_ips: old.allowed_ips.clone(),
+            }).await?;
+
+            let mut keys = self.keys.lock().unwrap();
+            if let Some(key) = keys.get_mut(prefix) {
+                let grace_ends = Utc::now() + grace;
+                key.expires_at = Some(key.expires_at.map_or(grace_ends, |e| e.min(grace_ends)));
+                key.rotated_to = Some(successor.id);
+            }
+            Ok(Some(successor))
+        }
     }
 }
 
@@ -381,6 +644,9 @@ mod tests {
             scopes: vec!["read".to_string()],
             expires_at: None,
             rate_limit_per_minute: None,
+            key_type: ApiKeyType::Secret,
+            allowed_origins: Vec::new(),
+            allowed_ips: Vec::new(),
         };
         
         let created = repo.create(request).await.unwrap();
@@ -404,6 +670,9 @@ mod tests {
             scopes: vec!["read".to_string()],
             expires_at: None,
             rate_limit_per_minute: None,
+            key_type: ApiKeyType::Secret,
+            allowed_origins: Vec::new(),
+            allowed_ips: Vec::new(),
         };
         
         repo.create(request).await.unwrap();
@@ -416,4 +685,80 @@ mod tests {
         let active = repo.find_active_by_prefix("test123").await.unwrap();
         assert!(active.is_none());
     }
+    
+    #[tokio::test]
+    async fn test_mock_rotate() {
+        let repo = MockApiKeyRepository::new();
+        
+        let request = CreateApiKeyRequest {
+            customer_id: None,
+            key_prefix: "pk_old".to_string(),
+            key_hash: "hash123".to_string(),
+            name: "Storefront".to_string(),
+            scopes: vec!["read".to_string()],
+            expires_at: None,
+            rate_limit_per_minute: Some(600),
+            key_type: ApiKeyType::Publishable,
+            allowed_origins: vec!["shop.example.com".to_string()],
+            allowed_ips: Vec::new(),
+        };
+        let old = repo.create(request).await.unwrap();
+        
+        let new = repo.rotate("pk_old", "pk_new", "hash456", chrono::Duration::hours(24))
+            .await.unwrap().unwrap();
+        assert_eq!(new.key_type, ApiKeyType::Publishable);
+        assert_eq!(new.allowed_origins, old.allowed_origins);
+        assert_eq!(new.rate_limit_per_minute, Some(600));
+        
+        // The old key stays usable during the grace period
+        let old = repo.find_by_prefix("pk_old").await.unwrap().unwrap();
+        assert_eq!(old.rotated_to, Some(new.id));
+        assert!(old.expires_at.is_some());
+        
+        assert!(repo.rotate("missing", "x", "y", chrono::Duration::hours(1)).await.unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_origin_matches() {
+        assert!(origin_matches("https://shop.example.com", "https://shop.example.com"));
+        assert!(origin_matches("https://shop.example.com/", "HTTPS://Shop.Example.com"));
+        assert!(!origin_matches("https://shop.example.com", "http://shop.example.com"));
+        assert!(!origin_matches("https://shop.example.com", "https://shop.example.com:8443"));
+        
+        assert!(origin_matches("shop.example.com", "http://shop.example.com:3000"));
+        assert!(!origin_matches("shop.example.com", "https://evilshop.example.com"));
+        assert!(origin_matches("localhost:3000", "http://localhost:3000"));
+        assert!(!origin_matches("localhost:3000", "http://localhost:4000"));
+        
+        assert!(origin_matches("*.example.com", "https://eu.shop.example.com"));
+        assert!(!origin_matches("*.example.com", "https://example.com"));
+        assert!(!origin_matches("*.example.com", "https://badexample.com"));
+        
+        assert!(!origin_matches("shop.example.com", "null"));
+        assert!(!origin_matches("shop.example.com", "shop.example.com"));
+    }
+    
+    #[test]
+    fn test_ip_matches() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(ip_matches("203.0.113.7", ip("203.0.113.7")));
+        assert!(!ip_matches("203.0.113.7", ip("203.0.113.8")));
+        assert!(ip_matches("10.0.0.0/8", ip("10.42.1.9")));
+        assert!(!ip_matches("10.0.0.0/8", ip("11.0.0.1")));
+        assert!(ip_matches("192.168.1.0/23", ip("192.168.0.200")));
+        assert!(ip_matches("0.0.0.0/0", ip("198.51.100.1")));
+        assert!(ip_matches("10.0.0.0/8", ip("::ffff:10.1.2.3")));
+        
+        assert!(ip_matches("2001:db8::/32", ip("2001:db8:abcd::1")));
+        assert!(!ip_matches("2001:db8::/32", ip("2001:db9::1")));
+        assert!(!ip_matches("2001:db8::/32", ip("10.0.0.1")));
+        
+        assert!(!ip_matches("10.0.0.0/33", ip("10.0.0.1")));
+        assert!(!ip_matches("not-an-ip", ip("10.0.0.1")));
+        
+        assert!(is_valid_ip_network("10.0.0.0/8"));
+        assert!(is_valid_ip_network("2001:db8::/128"));
+        assert!(!is_valid_ip_network("10.0.0.0/40"));
+        assert!(!is_valid_ip_network("example.com"));
+    }
 }
@@ -0,0 +1,268 @@
+//! Product attribute repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        Attribute, AttributeOption, AttributeOptionInput, AttributeWithOptions, CreateAttributeRequest,
+        NewAttributeValue, ProductAttributeValue, UpdateAttributeRequest,
+    },
+};
+
+/// Repository trait for attributes, their options and product values
+#[async_trait]
+pub trait AttributeRepository: Send + Sync {
+    async fn create(&self, request: &CreateAttributeRequest, filterable: bool) -> Result<AttributeWithOptions>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<AttributeWithOptions>>;
+
+    /// Attributes in position order, with their options
+    async fn list(&self) -> Result<Vec<AttributeWithOptions>>;
+
+    async fn update(&self, id: Uuid, request: &UpdateAttributeRequest) -> Result<Option<AttributeWithOptions>>;
+
+    /// Delete an attribute with its values; false if there was no such
+    /// attribute
+    async fn delete(&self, id: Uuid) -> Result<bool>;
+
+    /// Values of a product and its variants
+    async fn values(&self, product_id: Uuid) -> Result<Vec<ProductAttributeValue>>;
+
+    /// Replace the values of a product (None) or one of its variants; false
+    /// if the product (or the variant of it) does not exist
+    async fn set_values(&self, product_id: Uuid, variant_id: Option<Uuid>, values: &[NewAttributeValue]) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of AttributeRepository
+pub struct PostgresAttributeRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAttributeRepository {
+    /// Create a new PostgreSQL attribute repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+
+    async fn options(&self, attribute_ids: &[Uuid]) -> Result<Vec<AttributeOption>> {
+        sqlx::query_as::<_, AttributeOption>(
+            "SELECT * FROM attribute_options WHERE attribute_id = ANY($1) ORDER BY position, value"
+        )
+        .bind(attribute_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get attribute options: {}", e)))
+    }
+
+    async fn with_options(&self, attributes: Vec<Attribute>) -> Result<Vec<AttributeWithOptions>> {
+        let ids: Vec<Uuid> = attributes.iter().map(|a| a.id).collect();
+        let options = self.options(&ids).await?;
+        Ok(attributes
+            .into_iter()
+            .map(|attribute| AttributeWithOptions {
+                options: options.iter().filter(|o| o.attribute_id == attribute.id).cloned().collect(),
+                attribute,
+            })
+            .collect())
+    }
+
+    /// Insert or update options by value, in the order given
+    async fn upsert_options(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        attribute_id: Uuid,
+        options: &[AttributeOptionInput],
+    ) -> Result<()> {
+        for (position, option) in options.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO attribute_options (attribute_id, value, swatch, position)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (attribute_id, (LOWER(value))) DO UPDATE
+                SET value = EXCLUDED.value, swatch = EXCLUDED.swatch, position = EXCLUDED.position
+                "#
+            )
+            .bind(attribute_id)
+            .bind(option.value.trim())
+            .bind(option.swatch.as_deref().map(str::to_lowercase))
+            .bind(position as i32)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to save attribute option: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttributeRepository for PostgresAttributeRepository {
+    async fn create(&self, request: &CreateAttributeRequest, filterable: bool) -> Result<AttributeWithOptions> {
+        let mut tx = self.db.begin().await?;
+        let attribute = sqlx::query_as::<_, Attribute>(
+            r#"
+            INSERT INTO attributes (code, name, attribute_type, unit, filterable, position)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(request.code.trim())
+        .bind(request.name.trim())
+        .bind(request.attribute_type)
+        .bind(&request.unit)
+        .bind(filterable)
+        .bind(request.position)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation(format!("An attribute with code {} already exists", request.code.trim()))
+            }
+            e => Error::Other(format!("Failed to create attribute: {}", e)),
+        })?;
+        Self::upsert_options(&mut tx, attribute.id, &request.options).await?;
+        tx.commit().await?;
+
+        let options = self.options(&[attribute.id]).await?;
+        Ok(AttributeWithOptions { attribute, options })
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<AttributeWithOptions>> {
+        let attribute = sqlx::query_as::<_, Attribute>("SELECT * FROM attributes WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get attribute: {}", e)))?;
+        match attribute {
+            Some(attribute) => Ok(self.with_options(vec![attribute]).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<AttributeWithOptions>> {
+        let attributes = sqlx::query_as::<_, Attribute>("SELECT * FROM attributes ORDER BY position, name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list attributes: {}", e)))?;
+        self.with_options(attributes).await
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateAttributeRequest) -> Result<Option<AttributeWithOptions>> {
+        let mut tx = self.db.begin().await?;
+        let attribute = sqlx::query_as::<_, Attribute>(
+            r#"
+            UPDATE attributes
+            SET name = COALESCE($2, name),
+                unit = COALESCE($3, unit),
+                filterable = COALESCE($4, filterable),
+                position = COALESCE($5, position)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.name.as_deref().map(str::trim))
+        .bind(&request.unit)
+        .bind(request.filterable)
+        .bind(request.position)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update attribute: {}", e)))?;
+        let Some(attribute) = attribute else {
+            return Ok(None);
+        };
+
+        if let Some(options) = &request.options {
+            // Values of removed options go with them
+            let kept: Vec<String> = options.iter().map(|o| o.value.trim().to_lowercase()).collect();
+            sqlx::query("DELETE FROM attribute_options WHERE attribute_id = $1 AND NOT (LOWER(value) = ANY($2))")
+                .bind(id)
+                .bind(&kept)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to remove attribute options: {}", e)))?;
+            Self::upsert_options(&mut tx, id, options).await?;
+        }
+        tx.commit().await?;
+
+        let options = self.options(&[id]).await?;
+        Ok(Some(AttributeWithOptions { attribute, options }))
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM attributes WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete attribute: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn values(&self, product_id: Uuid) -> Result<Vec<ProductAttributeValue>> {
+        sqlx::query_as::<_, ProductAttributeValue>(
+            r#"
+            SELECT * FROM product_attribute_values
+            WHERE product_id = $1
+            ORDER BY variant_id NULLS FIRST, attribute_id, created_at
+            "#
+        )
+        .bind(product_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get product attribute values: {}", e)))
+    }
+
+    async fn set_values(&self, product_id: Uuid, variant_id: Option<Uuid>, values: &[NewAttributeValue]) -> Result<bool> {
+        let mut tx = self.db.begin().await?;
+        // Lock the product so concurrent sets replace rather than merge
+        let product = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT p.id FROM products p
+            WHERE p.id = $1 AND p.deleted_at IS NULL
+              AND ($2::uuid IS NULL OR EXISTS (
+                  SELECT 1 FROM product_variants v WHERE v.id = $2 AND v.product_id = p.id
+              ))
+            FOR UPDATE
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get product: {}", e)))?;
+        if product.is_none() {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM product_attribute_values WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2")
+            .bind(product_id)
+            .bind(variant_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to clear product attribute values: {}", e)))?;
+
+        for value in values {
+            sqlx::query(
+                r#"
+                INSERT INTO product_attribute_values
+                    (product_id, variant_id, attribute_id, option_id, text_value, number_value, boolean_value)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#
+            )
+            .bind(product_id)
+            .bind(variant_id)
+            .bind(value.attribute_id)
+            .bind(value.option_id)
+            .bind(&value.text_value)
+            .bind(value.number_value)
+            .bind(value.boolean_value)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to set product attribute value: {}", e)))?;
+        }
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,142 @@
+//! Audit log repository
+//!
+//! Appending audited calls, snapshotting the rows they touch, searching the
+//! log and purging entries past retention.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{AuditEntry, AuditFilter, NewAuditEntry},
+};
+
+/// Repository trait for the audit log
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Append an entry
+    async fn insert(&self, entry: &NewAuditEntry) -> Result<()>;
+
+    /// A row as JSON; `table` must come from the service's snapshot table list
+    async fn snapshot(&self, table: &str, id: Uuid) -> Result<Option<Value>>;
+
+    /// Matching entries, newest first
+    async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>>;
+
+    async fn get(&self, id: i64) -> Result<Option<AuditEntry>>;
+
+    /// Delete entries logged before a time; returns how many
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of AuditRepository
+pub struct PostgresAuditRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAuditRepository {
+    /// Create a new PostgreSQL audit repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for PostgresAuditRepository {
+    async fn insert(&self, entry: &NewAuditEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (
+                actor_type, actor_id, actor_label, method, path, route,
+                resource_type, resource_id, status, changes, client_ip
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#
+        )
+        .bind(entry.actor_type.as_str())
+        .bind(entry.actor_id)
+        .bind(&entry.actor_label)
+        .bind(&entry.method)
+        .bind(&entry.path)
+        .bind(&entry.route)
+        .bind(&entry.resource_type)
+        .bind(&entry.resource_id)
+        .bind(entry.status as i16)
+        .bind(&entry.changes)
+        .bind(&entry.client_ip)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to write audit log: {}", e)))?;
+        Ok(())
+    }
+
+    async fn snapshot(&self, table: &str, id: Uuid) -> Result<Option<Value>> {
+        sqlx::query_scalar::<_, Value>(&format!("SELECT to_jsonb(t) FROM {} t WHERE id = $1", table))
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to snapshot {}: {}", table, e)))
+    }
+
+    async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>> {
+        sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT * FROM audit_log
+            WHERE ($1::uuid IS NULL OR actor_id = $1)
+              AND ($2::text IS NULL OR actor_type = $2)
+              AND ($3::text IS NULL OR resource_type = $3)
+              AND ($4::text IS NULL OR resource_id = $4)
+              AND ($5::text IS NULL OR method = $5)
+              AND ($6::text IS NULL OR starts_with(path, $6))
+              AND ($7::text IS NULL OR changes ? $7)
+              AND ($8::timestamptz IS NULL OR created_at >= $8)
+              AND ($9::timestamptz IS NULL OR created_at < $9)
+              AND ($10::int8 IS NULL OR id < $10)
+            ORDER BY id DESC
+            LIMIT $11
+            "#
+        )
+        .bind(filter.actor_id)
+        .bind(&filter.actor_type)
+        .bind(&filter.resource_type)
+        .bind(&filter.resource_id)
+        .bind(filter.method.as_ref().map(|m| m.to_ascii_uppercase()))
+        .bind(&filter.path)
+        .bind(&filter.field)
+        .bind(filter.since)
+        .bind(filter.until)
+        .bind(filter.before_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to search audit log: {}", e)))
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<AuditEntry>> {
+        sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get audit entry: {}", e)))
+    }
+
+    async fn purge_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+        // The append-only trigger lets deletes through only with this set
+        sqlx::query("SET LOCAL rcommerce.audit_purge = 'on'")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge audit log: {}", e)))?;
+        let result = sqlx::query("DELETE FROM audit_log WHERE created_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge audit log: {}", e)))?;
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
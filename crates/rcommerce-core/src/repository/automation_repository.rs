@@ -0,0 +1,342 @@
+//! Automation repository
+//!
+//! Automation rules, the events database triggers queue for them, run logs,
+//! the contexts rules are evaluated against and the order changes their
+//! actions make.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::models::{
+    ActionOutcome, AutomationEvent, AutomationRule, AutomationRun, AutomationTrigger, CreateAutomationRuleRequest,
+};
+use crate::{Error, Result};
+
+/// Repository trait for automation rules, events and runs
+#[async_trait]
+pub trait AutomationRepository: Send + Sync {
+    async fn create_rule(&self, request: &CreateAutomationRuleRequest) -> Result<AutomationRule>;
+
+    async fn find_rule(&self, id: Uuid) -> Result<Option<AutomationRule>>;
+
+    /// Rules by trigger and position
+    async fn list_rules(&self, trigger: Option<AutomationTrigger>, enabled_only: bool) -> Result<Vec<AutomationRule>>;
+
+    /// Replace a rule; None if it does not exist
+    async fn update_rule(&self, id: Uuid, rule: &CreateAutomationRuleRequest) -> Result<Option<AutomationRule>>;
+
+    /// Delete a rule with its runs; false if it did not exist
+    async fn delete_rule(&self, id: Uuid) -> Result<bool>;
+
+    /// Unprocessed events, oldest first
+    async fn pending_events(&self, limit: i64) -> Result<Vec<AutomationEvent>>;
+
+    async fn mark_processed(&self, event_id: Uuid) -> Result<()>;
+
+    /// Delete processed events older than `days`
+    async fn purge_events(&self, days: u32) -> Result<u64>;
+
+    /// The `order` context object; None if the order does not exist
+    async fn order_context(&self, order_id: Uuid) -> Result<Option<Value>>;
+
+    /// The `customer` context object; None if the customer does not exist
+    async fn customer_context(&self, customer_id: Uuid) -> Result<Option<Value>>;
+
+    /// Add a tag; false if the order had it or does not exist
+    async fn add_order_tag(&self, order_id: Uuid, tag: &str) -> Result<bool>;
+
+    /// Remove a tag; false if the order did not have it
+    async fn remove_order_tag(&self, order_id: Uuid, tag: &str) -> Result<bool>;
+
+    /// Put an order on hold; false if its status can't be held
+    async fn hold_order(&self, order_id: Uuid) -> Result<bool>;
+
+    async fn record_run(
+        &self,
+        rule_id: Uuid,
+        event: &AutomationEvent,
+        status: &str,
+        actions: &[ActionOutcome],
+    ) -> Result<AutomationRun>;
+
+    /// Runs, newest first
+    async fn list_runs(&self, rule_id: Option<Uuid>, order_id: Option<Uuid>, limit: i64) -> Result<Vec<AutomationRun>>;
+}
+
+/// PostgreSQL implementation of AutomationRepository
+#[derive(Clone)]
+pub struct PostgresAutomationRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresAutomationRepository {
+    /// Create a new PostgreSQL automation repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AutomationRepository for PostgresAutomationRepository {
+    async fn create_rule(&self, request: &CreateAutomationRuleRequest) -> Result<AutomationRule> {
+        sqlx::query_as::<_, AutomationRule>(
+            r#"
+            INSERT INTO automation_rules (name, description, trigger, conditions, actions, enabled, position)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(request.name.trim())
+        .bind(&request.description)
+        .bind(request.trigger)
+        .bind(Json(&request.conditions))
+        .bind(Json(&request.actions))
+        .bind(request.enabled)
+        .bind(request.position)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create automation rule: {}", e)))
+    }
+
+    async fn find_rule(&self, id: Uuid) -> Result<Option<AutomationRule>> {
+        sqlx::query_as::<_, AutomationRule>("SELECT * FROM automation_rules WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get automation rule: {}", e)))
+    }
+
+    async fn list_rules(&self, trigger: Option<AutomationTrigger>, enabled_only: bool) -> Result<Vec<AutomationRule>> {
+        sqlx::query_as::<_, AutomationRule>(
+            r#"
+            SELECT * FROM automation_rules
+            WHERE ($1::automation_trigger IS NULL OR trigger = $1)
+              AND (enabled OR NOT $2)
+            ORDER BY trigger, position, created_at
+            "#
+        )
+        .bind(trigger)
+        .bind(enabled_only)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list automation rules: {}", e)))
+    }
+
+    async fn update_rule(&self, id: Uuid, rule: &CreateAutomationRuleRequest) -> Result<Option<AutomationRule>> {
+        sqlx::query_as::<_, AutomationRule>(
+            r#"
+            UPDATE automation_rules
+            SET name = $2, description = $3, trigger = $4, conditions = $5, actions = $6,
+                enabled = $7, position = $8
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(rule.name.trim())
+        .bind(&rule.description)
+        .bind(rule.trigger)
+        .bind(Json(&rule.conditions))
+        .bind(Json(&rule.actions))
+        .bind(rule.enabled)
+        .bind(rule.position)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update automation rule: {}", e)))
+    }
+
+    async fn delete_rule(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM automation_rules WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete automation rule: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn pending_events(&self, limit: i64) -> Result<Vec<AutomationEvent>> {
+        sqlx::query_as::<_, AutomationEvent>(
+            "SELECT * FROM automation_events WHERE processed_at IS NULL ORDER BY created_at LIMIT $1"
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get automation events: {}", e)))
+    }
+
+    async fn mark_processed(&self, event_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE automation_events SET processed_at = NOW() WHERE id = $1")
+            .bind(event_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update automation event: {}", e)))?;
+        Ok(())
+    }
+
+    async fn purge_events(&self, days: u32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM automation_events WHERE processed_at < NOW() - make_interval(days => $1)"
+        )
+        .bind(days as i32)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to purge automation events: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn order_context(&self, order_id: Uuid) -> Result<Option<Value>> {
+        sqlx::query_scalar::<_, Value>(
+            r#"
+            SELECT jsonb_build_object(
+                'id', o.id,
+                'order_number', o.order_number,
+                'customer_id', o.customer_id,
+                'email', o.email,
+                'status', o.status,
+                'payment_status', o.payment_status,
+                'fulfillment_status', o.fulfillment_status,
+                'order_type', o.order_type,
+                'channel', o.channel,
+                'currency', o.currency,
+                'subtotal', o.subtotal,
+                'tax_total', o.tax_total,
+                'shipping_total', o.shipping_total,
+                'discount_total', o.discount_total,
+                'total', o.total,
+                'item_count', (SELECT COALESCE(SUM(quantity), 0) FROM order_items WHERE order_id = o.id),
+                'skus', (SELECT COALESCE(jsonb_agg(DISTINCT sku) FILTER (WHERE sku IS NOT NULL), '[]'::JSONB)
+                         FROM order_items WHERE order_id = o.id),
+                'tags', to_jsonb(COALESCE(o.tags, ARRAY[]::TEXT[])),
+                'coupon_code', o.coupon_code,
+                'payment_method', o.payment_method,
+                'shipping_method', o.shipping_method,
+                'shipping_country', COALESCE(sa.country, o.shipping_address->>'country'),
+                'billing_country', COALESCE(ba.country, o.billing_address->>'country'),
+                'created_at', o.created_at
+            )
+            FROM orders o
+            LEFT JOIN addresses sa ON sa.id = o.shipping_address_id
+            LEFT JOIN addresses ba ON ba.id = o.billing_address_id
+            WHERE o.id = $1
+            "#
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order for automation: {}", e)))
+    }
+
+    async fn customer_context(&self, customer_id: Uuid) -> Result<Option<Value>> {
+        sqlx::query_scalar::<_, Value>(
+            r#"
+            SELECT jsonb_build_object(
+                'id', c.id,
+                'email', c.email,
+                'first_name', c.first_name,
+                'last_name', c.last_name,
+                'phone', c.phone,
+                'accepts_marketing', c.accepts_marketing,
+                'tax_exempt', c.tax_exempt,
+                'currency', c.currency,
+                'order_count', (SELECT COUNT(*) FROM orders WHERE customer_id = c.id),
+                'total_spent', (SELECT COALESCE(SUM(total), 0) FROM orders
+                                WHERE customer_id = c.id AND payment_status = 'paid'),
+                'created_at', c.created_at
+            )
+            FROM customers c
+            WHERE c.id = $1
+            "#
+        )
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get customer for automation: {}", e)))
+    }
+
+    async fn add_order_tag(&self, order_id: Uuid, tag: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE orders SET tags = array_append(COALESCE(tags, ARRAY[]::TEXT[]), $2), updated_at = NOW()
+            WHERE id = $1 AND NOT ($2 = ANY(COALESCE(tags, ARRAY[]::TEXT[])))
+            "#
+        )
+        .bind(order_id)
+        .bind(tag)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to tag order: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove_order_tag(&self, order_id: Uuid, tag: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE orders SET tags = array_remove(tags, $2), updated_at = NOW() WHERE id = $1 AND $2 = ANY(tags)"
+        )
+        .bind(order_id)
+        .bind(tag)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to untag order: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn hold_order(&self, order_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE orders SET status = 'on_hold', updated_at = NOW()
+            WHERE id = $1 AND status IN ('pending', 'confirmed', 'processing')
+            "#
+        )
+        .bind(order_id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to hold order: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_run(
+        &self,
+        rule_id: Uuid,
+        event: &AutomationEvent,
+        status: &str,
+        actions: &[ActionOutcome],
+    ) -> Result<AutomationRun> {
+        sqlx::query_as::<_, AutomationRun>(
+            r#"
+            INSERT INTO automation_runs (rule_id, event_id, trigger, order_id, customer_id, status, actions)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(rule_id)
+        .bind(event.id)
+        .bind(event.trigger)
+        .bind(event.order_id)
+        .bind(event.customer_id)
+        .bind(status)
+        .bind(Json(actions))
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record automation run: {}", e)))
+    }
+
+    async fn list_runs(&self, rule_id: Option<Uuid>, order_id: Option<Uuid>, limit: i64) -> Result<Vec<AutomationRun>> {
+        sqlx::query_as::<_, AutomationRun>(
+            r#"
+            SELECT * FROM automation_runs
+            WHERE ($1::uuid IS NULL OR rule_id = $1)
+              AND ($2::uuid IS NULL OR order_id = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(rule_id)
+        .bind(order_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list automation runs: {}", e)))
+    }
+}
@@ -0,0 +1,484 @@
+//! Catalog promotion repository
+//!
+//! Reads an instance's catalog as a snapshot keyed by slugs and codes, and
+//! applies promoted changes, resolving those keys to this instance's IDs.
+
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::{
+    CatalogApplyResult, CatalogCategory, CatalogChange, CatalogChangeAction, CatalogEntity, CatalogProduct,
+    CatalogSnapshot, CatalogTaxCategory, CatalogTaxRate, CatalogTaxZone,
+};
+use crate::shipping::ShippingRule;
+use crate::{Error, Result};
+
+/// Repository trait for catalog promotion
+#[async_trait]
+pub trait CatalogRepository: Send + Sync {
+    async fn snapshot(&self) -> Result<CatalogSnapshot>;
+
+    /// Apply changes in the order given, all or nothing
+    async fn apply(&self, changes: &[CatalogChange]) -> Result<CatalogApplyResult>;
+}
+
+/// PostgreSQL implementation of CatalogRepository
+#[derive(Clone)]
+pub struct PostgresCatalogRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresCatalogRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CatalogRepository for PostgresCatalogRepository {
+    async fn snapshot(&self) -> Result<CatalogSnapshot> {
+        let tax_categories = sqlx::query_as::<_, CatalogTaxCategory>(
+            r#"
+            SELECT code, name, description, is_digital, is_food, is_luxury, is_medical, is_educational
+            FROM tax_categories
+            ORDER BY code
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list tax categories: {}", e)))?;
+
+        let tax_zones = sqlx::query_as::<_, CatalogTaxZone>(
+            r#"
+            SELECT z.code, z.name, z.country_code::text AS country_code, z.region_code,
+                   z.postal_code_pattern, z.zone_type, p.code AS parent_code
+            FROM tax_zones z
+            LEFT JOIN tax_zones p ON p.id = z.parent_id
+            ORDER BY z.code
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list tax zones: {}", e)))?;
+
+        let categories = sqlx::query_as::<_, CatalogCategory>(
+            r#"
+            SELECT c.slug, c.name, c.description, p.slug AS parent_slug, c.image_url,
+                   c.seo_title, c.seo_description, c.sort_order, c.is_active
+            FROM product_categories c
+            LEFT JOIN product_categories p ON p.id = c.parent_id
+            ORDER BY c.slug
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list categories: {}", e)))?;
+
+        let products = sqlx::query_as::<_, CatalogProduct>(
+            r#"
+            SELECT p.slug, p.title, p.description, p.sku, p.product_type::text AS product_type,
+                   p.price, p.compare_at_price, p.cost_price, p.currency::text AS currency,
+                   p.weight, p.weight_unit::text AS weight_unit, p.requires_shipping,
+                   p.is_active, p.is_featured, p.seo_title, p.seo_description,
+                   tc.code AS tax_category_code,
+                   ARRAY(
+                       SELECT c.slug::text
+                       FROM product_category_relations r
+                       JOIN product_categories c ON c.id = r.category_id
+                       WHERE r.product_id = p.id
+                       ORDER BY c.slug
+                   ) AS category_slugs,
+                   (
+                       SELECT c.slug
+                       FROM product_category_relations r
+                       JOIN product_categories c ON c.id = r.category_id
+                       WHERE r.product_id = p.id AND r.is_primary
+                       ORDER BY c.slug
+                       LIMIT 1
+                   ) AS primary_category_slug
+            FROM products p
+            LEFT JOIN tax_categories tc ON tc.id = p.tax_category_id
+            ORDER BY p.slug
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list products: {}", e)))?;
+
+        let tax_rates = sqlx::query_as::<_, CatalogTaxRate>(
+            r#"
+            SELECT z.code AS zone_code, tc.code AS category_code, r.valid_from, r.name, r.rate,
+                   r.rate_type, r.is_vat, r.vat_type, r.b2b_exempt, r.reverse_charge,
+                   r.valid_until, r.priority
+            FROM tax_rates r
+            JOIN tax_zones z ON z.id = r.tax_zone_id
+            LEFT JOIN tax_categories tc ON tc.id = r.tax_category_id
+            ORDER BY z.code, tc.code NULLS FIRST, r.valid_from
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list tax rates: {}", e)))?;
+
+        let rows = sqlx::query_as::<_, (String, serde_json::Value, serde_json::Value, i32, bool)>(
+            "SELECT name, condition, action, priority, enabled FROM shipping_rules ORDER BY name",
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list shipping rules: {}", e)))?;
+        let shipping_rules = rows
+            .into_iter()
+            .map(|(name, condition, action, priority, enabled)| {
+                let invalid = |e: serde_json::Error| Error::Other(format!("Invalid shipping rule '{}': {}", name, e));
+                Ok(ShippingRule {
+                    condition: serde_json::from_value(condition).map_err(invalid)?,
+                    action: serde_json::from_value(action).map_err(invalid)?,
+                    name: name.clone(),
+                    priority,
+                    enabled,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CatalogSnapshot {
+            generated_at: Some(chrono::Utc::now()),
+            tax_categories,
+            tax_zones,
+            categories,
+            products,
+            tax_rates,
+            shipping_rules,
+        })
+    }
+
+    async fn apply(&self, changes: &[CatalogChange]) -> Result<CatalogApplyResult> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let mut result = CatalogApplyResult::default();
+        // Parents are linked once every zone and category in the batch exists
+        let mut zone_parents = Vec::new();
+        let mut category_parents = Vec::new();
+
+        for change in changes {
+            let failed = |e: sqlx::Error| {
+                Error::Other(format!("Failed to apply {} '{}': {}", change.entity, change.key, e))
+            };
+
+            if change.action == CatalogChangeAction::Delete {
+                delete(&mut tx, change).await.map_err(failed)?;
+                result.deleted += 1;
+                continue;
+            }
+
+            match change.entity {
+                CatalogEntity::TaxCategory => {
+                    let category: CatalogTaxCategory = change.decode()?;
+                    sqlx::query(
+                        r#"
+                        INSERT INTO tax_categories (
+                            code, name, description, is_digital, is_food, is_luxury, is_medical, is_educational
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                        ON CONFLICT (code) DO UPDATE SET
+                            name = EXCLUDED.name, description = EXCLUDED.description,
+                            is_digital = EXCLUDED.is_digital, is_food = EXCLUDED.is_food,
+                            is_luxury = EXCLUDED.is_luxury, is_medical = EXCLUDED.is_medical,
+                            is_educational = EXCLUDED.is_educational, updated_at = NOW()
+                        "#,
+                    )
+                    .bind(&category.code)
+                    .bind(&category.name)
+                    .bind(&category.description)
+                    .bind(category.is_digital)
+                    .bind(category.is_food)
+                    .bind(category.is_luxury)
+                    .bind(category.is_medical)
+                    .bind(category.is_educational)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+                }
+                CatalogEntity::TaxZone => {
+                    let zone: CatalogTaxZone = change.decode()?;
+                    sqlx::query(
+                        r#"
+                        INSERT INTO tax_zones (code, name, country_code, region_code, postal_code_pattern, zone_type)
+                        VALUES ($1, $2, $3, $4, $5, $6)
+                        ON CONFLICT (code) DO UPDATE SET
+                            name = EXCLUDED.name, country_code = EXCLUDED.country_code,
+                            region_code = EXCLUDED.region_code, postal_code_pattern = EXCLUDED.postal_code_pattern,
+                            zone_type = EXCLUDED.zone_type, updated_at = NOW()
+                        "#,
+                    )
+                    .bind(&zone.code)
+                    .bind(&zone.name)
+                    .bind(&zone.country_code)
+                    .bind(&zone.region_code)
+                    .bind(&zone.postal_code_pattern)
+                    .bind(&zone.zone_type)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+                    zone_parents.push((zone.code, zone.parent_code));
+                }
+                CatalogEntity::Category => {
+                    let category: CatalogCategory = change.decode()?;
+                    sqlx::query(
+                        r#"
+                        INSERT INTO product_categories (
+                            slug, name, description, image_url, seo_title, seo_description, sort_order, is_active
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                        ON CONFLICT (slug) DO UPDATE SET
+                            name = EXCLUDED.name, description = EXCLUDED.description,
+                            image_url = EXCLUDED.image_url, seo_title = EXCLUDED.seo_title,
+                            seo_description = EXCLUDED.seo_description, sort_order = EXCLUDED.sort_order,
+                            is_active = EXCLUDED.is_active, updated_at = NOW()
+                        "#,
+                    )
+                    .bind(&category.slug)
+                    .bind(&category.name)
+                    .bind(&category.description)
+                    .bind(&category.image_url)
+                    .bind(&category.seo_title)
+                    .bind(&category.seo_description)
+                    .bind(category.sort_order)
+                    .bind(category.is_active)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+                    category_parents.push((category.slug, category.parent_slug));
+                }
+                CatalogEntity::Product => {
+                    let product: CatalogProduct = change.decode()?;
+                    let tax_category_id = match product.tax_category_code {
+                        Some(ref code) => Some(find_id(&mut tx, CatalogEntity::TaxCategory, code).await?),
+                        None => None,
+                    };
+                    let product_id: Uuid = sqlx::query_scalar(
+                        r#"
+                        INSERT INTO products (
+                            slug, title, description, sku, product_type, price, compare_at_price, cost_price,
+                            currency, weight, weight_unit, requires_shipping, is_active, is_featured,
+                            seo_title, seo_description, tax_category_id
+                        )
+                        VALUES (
+                            $1, $2, $3, $4, $5::product_type, $6, $7, $8, $9::currency, $10,
+                            $11::weight_unit, $12, $13, $14, $15, $16, $17
+                        )
+                        ON CONFLICT (slug) DO UPDATE SET
+                            title = EXCLUDED.title, description = EXCLUDED.description, sku = EXCLUDED.sku,
+                            product_type = EXCLUDED.product_type, price = EXCLUDED.price,
+                            compare_at_price = EXCLUDED.compare_at_price, cost_price = EXCLUDED.cost_price,
+                            currency = EXCLUDED.currency, weight = EXCLUDED.weight,
+                            weight_unit = EXCLUDED.weight_unit, requires_shipping = EXCLUDED.requires_shipping,
+                            is_active = EXCLUDED.is_active, is_featured = EXCLUDED.is_featured,
+                            seo_title = EXCLUDED.seo_title, seo_description = EXCLUDED.seo_description,
+                            tax_category_id = EXCLUDED.tax_category_id, updated_at = NOW()
+                        RETURNING id
+                        "#,
+                    )
+                    .bind(&product.slug)
+                    .bind(&product.title)
+                    .bind(&product.description)
+                    .bind(&product.sku)
+                    .bind(&product.product_type)
+                    .bind(product.price)
+                    .bind(product.compare_at_price)
+                    .bind(product.cost_price)
+                    .bind(&product.currency)
+                    .bind(product.weight)
+                    .bind(&product.weight_unit)
+                    .bind(product.requires_shipping)
+                    .bind(product.is_active)
+                    .bind(product.is_featured)
+                    .bind(&product.seo_title)
+                    .bind(&product.seo_description)
+                    .bind(tax_category_id)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+
+                    sqlx::query("DELETE FROM product_category_relations WHERE product_id = $1")
+                        .bind(product_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(failed)?;
+                    for slug in &product.category_slugs {
+                        let category_id = find_id(&mut tx, CatalogEntity::Category, slug).await?;
+                        sqlx::query(
+                            "INSERT INTO product_category_relations (product_id, category_id, is_primary) VALUES ($1, $2, $3)",
+                        )
+                        .bind(product_id)
+                        .bind(category_id)
+                        .bind(product.primary_category_slug.as_ref() == Some(slug))
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(failed)?;
+                    }
+                }
+                CatalogEntity::TaxRate => {
+                    let rate: CatalogTaxRate = change.decode()?;
+                    let zone_id = find_id(&mut tx, CatalogEntity::TaxZone, &rate.zone_code).await?;
+                    let category_id = match rate.category_code {
+                        Some(ref code) => Some(find_id(&mut tx, CatalogEntity::TaxCategory, code).await?),
+                        None => None,
+                    };
+                    // The unique key has a nullable category, so no ON CONFLICT
+                    let updated = sqlx::query(
+                        r#"
+                        UPDATE tax_rates SET
+                            name = $4, rate = $5, rate_type = $6, is_vat = $7, vat_type = $8,
+                            b2b_exempt = $9, reverse_charge = $10, valid_until = $11, priority = $12,
+                            updated_at = NOW()
+                        WHERE tax_zone_id = $1 AND tax_category_id IS NOT DISTINCT FROM $2 AND valid_from = $3
+                        "#,
+                    )
+                    .bind(zone_id)
+                    .bind(category_id)
+                    .bind(rate.valid_from)
+                    .bind(&rate.name)
+                    .bind(rate.rate)
+                    .bind(&rate.rate_type)
+                    .bind(rate.is_vat)
+                    .bind(&rate.vat_type)
+                    .bind(rate.b2b_exempt)
+                    .bind(rate.reverse_charge)
+                    .bind(rate.valid_until)
+                    .bind(rate.priority)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+                    if updated.rows_affected() == 0 {
+                        sqlx::query(
+                            r#"
+                            INSERT INTO tax_rates (
+                                tax_zone_id, tax_category_id, valid_from, name, rate, rate_type, is_vat,
+                                vat_type, b2b_exempt, reverse_charge, valid_until, priority
+                            )
+                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                            "#,
+                        )
+                        .bind(zone_id)
+                        .bind(category_id)
+                        .bind(rate.valid_from)
+                        .bind(&rate.name)
+                        .bind(rate.rate)
+                        .bind(&rate.rate_type)
+                        .bind(rate.is_vat)
+                        .bind(&rate.vat_type)
+                        .bind(rate.b2b_exempt)
+                        .bind(rate.reverse_charge)
+                        .bind(rate.valid_until)
+                        .bind(rate.priority)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(failed)?;
+                    }
+                }
+                CatalogEntity::ShippingRule => {
+                    let rule: ShippingRule = change.decode()?;
+                    let encode = |value: serde_json::Result<serde_json::Value>| {
+                        value.map_err(|e| Error::Other(format!("Failed to encode shipping rule: {}", e)))
+                    };
+                    sqlx::query(
+                        r#"
+                        INSERT INTO shipping_rules (name, condition, action, priority, enabled)
+                        VALUES ($1, $2, $3, $4, $5)
+                        ON CONFLICT (name) DO UPDATE SET
+                            condition = EXCLUDED.condition, action = EXCLUDED.action,
+                            priority = EXCLUDED.priority, enabled = EXCLUDED.enabled, updated_at = NOW()
+                        "#,
+                    )
+                    .bind(&rule.name)
+                    .bind(encode(serde_json::to_value(&rule.condition))?)
+                    .bind(encode(serde_json::to_value(&rule.action))?)
+                    .bind(rule.priority)
+                    .bind(rule.enabled)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(failed)?;
+                }
+            }
+
+            match change.action {
+                CatalogChangeAction::Create => result.created += 1,
+                _ => result.updated += 1,
+            }
+        }
+
+        for (code, parent_code) in zone_parents {
+            let parent_id = match parent_code {
+                Some(ref parent) => Some(find_id(&mut tx, CatalogEntity::TaxZone, parent).await?),
+                None => None,
+            };
+            sqlx::query("UPDATE tax_zones SET parent_id = $2 WHERE code = $1")
+                .bind(&code)
+                .bind(parent_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to set parent of tax zone '{}': {}", code, e)))?;
+        }
+        for (slug, parent_slug) in category_parents {
+            let parent_id = match parent_slug {
+                Some(ref parent) => Some(find_id(&mut tx, CatalogEntity::Category, parent).await?),
+                None => None,
+            };
+            sqlx::query("UPDATE product_categories SET parent_id = $2 WHERE slug = $1")
+                .bind(&slug)
+                .bind(parent_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to set parent of category '{}': {}", slug, e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(result)
+    }
+}
+
+/// This instance's ID for a zone, category or tax category key
+async fn find_id(conn: &mut PgConnection, entity: CatalogEntity, key: &str) -> Result<Uuid> {
+    let sql = match entity {
+        CatalogEntity::TaxCategory => "SELECT id FROM tax_categories WHERE code = $1",
+        CatalogEntity::TaxZone => "SELECT id FROM tax_zones WHERE code = $1",
+        CatalogEntity::Category => "SELECT id FROM product_categories WHERE slug = $1",
+        _ => return Err(Error::Other(format!("Cannot look up {} records", entity))),
+    };
+    sqlx::query_scalar::<_, Uuid>(sql)
+        .bind(key)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch {} '{}': {}", entity, key, e)))?
+        .ok_or_else(|| Error::validation(format!("{} '{}' does not exist on this instance", entity, key)))
+}
+
+async fn delete(conn: &mut PgConnection, change: &CatalogChange) -> std::result::Result<(), sqlx::Error> {
+    let sql = match change.entity {
+        CatalogEntity::TaxCategory => "DELETE FROM tax_categories WHERE code = $1",
+        CatalogEntity::TaxZone => "DELETE FROM tax_zones WHERE code = $1",
+        CatalogEntity::Category => "DELETE FROM product_categories WHERE slug = $1",
+        // Order items reference products, so they are only deactivated
+        CatalogEntity::Product => "UPDATE products SET is_active = false, updated_at = NOW() WHERE slug = $1",
+        CatalogEntity::ShippingRule => "DELETE FROM shipping_rules WHERE name = $1",
+        CatalogEntity::TaxRate => {
+            return sqlx::query(
+                r#"
+                DELETE FROM tax_rates r
+                USING tax_zones z
+                WHERE z.id = r.tax_zone_id
+                  AND z.code || '/' || COALESCE((SELECT code FROM tax_categories WHERE id = r.tax_category_id), '*')
+                      || '/' || r.valid_from::text = $1
+                "#,
+            )
+            .bind(&change.key)
+            .execute(conn)
+            .await
+            .map(|_| ());
+        }
+    };
+    sqlx::query(sql).bind(&change.key).execute(conn).await.map(|_| ())
+}
@@ -1,6 +1,7 @@
 //! Product category repository for database operations
 
 use async_trait::async_trait;
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::{
@@ -58,9 +59,10 @@ pub trait CategoryRepository: Send + Sync {
 }
 
 /// Tree node for category hierarchy
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 pub struct CategoryTreeNode {
     pub category: ProductCategory,
+    #[schema(no_recursion)]
     pub children: Vec<CategoryTreeNode>,
 }
 
@@ -105,8 +107,8 @@ impl CategoryRepository for PostgresCategoryRepository {
     async fn create(&self, category: &ProductCategory) -> Result<ProductCategory> {
         let category = sqlx::query_as::<_, ProductCategory>(
             r#"
-            INSERT INTO product_categories (id, name, slug, description, parent_id, sort_order)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO product_categories (id, name, slug, description, parent_id, sort_order, image_url, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING *
             "#
         )
@@ -116,6 +118,8 @@ impl CategoryRepository for PostgresCategoryRepository {
         .bind(&category.description)
         .bind(category.parent_id)
         .bind(category.sort_order)
+        .bind(&category.image_url)
+        .bind(category.is_active)
         .fetch_one(&self.db)
         .await
         .map_err(|e| Error::Other(format!("Failed to create category: {}", e)))?;
@@ -132,8 +136,10 @@ impl CategoryRepository for PostgresCategoryRepository {
                 description = $3,
                 parent_id = $4,
                 sort_order = $5,
+                image_url = $6,
+                is_active = $7,
                 updated_at = NOW()
-            WHERE id = $6
+            WHERE id = $8
             RETURNING *
             "#
         )
@@ -142,6 +148,8 @@ impl CategoryRepository for PostgresCategoryRepository {
         .bind(&category.description)
         .bind(category.parent_id)
         .bind(category.sort_order)
+        .bind(&category.image_url)
+        .bind(category.is_active)
         .bind(category.id)
         .fetch_one(&self.db)
         .await
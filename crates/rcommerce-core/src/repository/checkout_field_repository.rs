@@ -0,0 +1,54 @@
+//! Custom checkout field repository
+
+use async_trait::async_trait;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::CheckoutFieldValues,
+};
+
+/// Repository trait for custom checkout field answers on orders
+#[async_trait]
+pub trait CheckoutFieldRepository: Send + Sync {
+    /// Record an order's answers, replacing any earlier ones; false if the order does not exist
+    async fn save_for_order(&self, order_id: Uuid, values: &CheckoutFieldValues) -> Result<bool>;
+
+    /// An order's answers, if the order exists
+    async fn for_order(&self, order_id: Uuid) -> Result<Option<CheckoutFieldValues>>;
+}
+
+/// PostgreSQL implementation of CheckoutFieldRepository
+pub struct PostgresCheckoutFieldRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresCheckoutFieldRepository {
+    /// Create a new PostgreSQL checkout field repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CheckoutFieldRepository for PostgresCheckoutFieldRepository {
+    async fn save_for_order(&self, order_id: Uuid, values: &CheckoutFieldValues) -> Result<bool> {
+        let result = sqlx::query("UPDATE orders SET custom_fields = $2, updated_at = NOW() WHERE id = $1")
+            .bind(order_id)
+            .bind(Json(values))
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to save checkout fields: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn for_order(&self, order_id: Uuid) -> Result<Option<CheckoutFieldValues>> {
+        let values = sqlx::query_scalar::<_, Json<CheckoutFieldValues>>("SELECT custom_fields FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get checkout fields: {}", e)))?;
+        Ok(values.map(|Json(values)| values))
+    }
+}
@@ -0,0 +1,195 @@
+//! Collection repository
+
+use async_trait::async_trait;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{Collection, CreateCollectionRequest, UpdateCollectionRequest},
+};
+
+/// Repository trait for collections and their manual members
+#[async_trait]
+pub trait CollectionRepository: Send + Sync {
+    /// Collections by title; only the published ones when `published_only`
+    async fn list(&self, published_only: bool) -> Result<Vec<Collection>>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<Collection>>;
+
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Collection>>;
+
+    async fn create(&self, request: &CreateCollectionRequest, handle: &str, sort_order: &str) -> Result<Collection>;
+
+    /// None if there is no such collection
+    async fn update(&self, id: Uuid, request: &UpdateCollectionRequest) -> Result<Option<Collection>>;
+
+    /// Delete a collection with its members; false if there was no such
+    /// collection
+    async fn delete(&self, id: Uuid) -> Result<bool>;
+
+    /// Add products to the end of a manual collection, skipping those
+    /// already in it or that do not exist; the number added
+    async fn add_products(&self, collection_id: Uuid, product_ids: &[Uuid]) -> Result<u64>;
+
+    /// Remove a product from a manual collection; false if it was not there
+    async fn remove_product(&self, collection_id: Uuid, product_id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of CollectionRepository
+pub struct PostgresCollectionRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresCollectionRepository {
+    /// Create a new PostgreSQL collection repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CollectionRepository for PostgresCollectionRepository {
+    async fn list(&self, published_only: bool) -> Result<Vec<Collection>> {
+        sqlx::query_as::<_, Collection>(
+            r#"
+            SELECT * FROM collections
+            WHERE NOT $1 OR (published_at IS NOT NULL AND published_at <= NOW())
+            ORDER BY title
+            "#
+        )
+        .bind(published_only)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list collections: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<Collection>> {
+        sqlx::query_as::<_, Collection>("SELECT * FROM collections WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get collection: {}", e)))
+    }
+
+    async fn find_by_handle(&self, handle: &str) -> Result<Option<Collection>> {
+        sqlx::query_as::<_, Collection>("SELECT * FROM collections WHERE handle = $1")
+            .bind(handle)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get collection by handle: {}", e)))
+    }
+
+    async fn create(&self, request: &CreateCollectionRequest, handle: &str, sort_order: &str) -> Result<Collection> {
+        sqlx::query_as::<_, Collection>(
+            r#"
+            INSERT INTO collections (
+                title, handle, description, seo_title, seo_description, collection_type,
+                rules, disjunctive, sort_order, published_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CASE WHEN $10 THEN NOW() END)
+            RETURNING *
+            "#
+        )
+        .bind(request.title.trim())
+        .bind(handle)
+        .bind(&request.description)
+        .bind(&request.seo_title)
+        .bind(&request.seo_description)
+        .bind(request.collection_type)
+        .bind(Json(&request.rules))
+        .bind(request.disjunctive)
+        .bind(sort_order)
+        .bind(request.published)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation(format!("A collection with the handle {} already exists", handle))
+            }
+            e => Error::Other(format!("Failed to create collection: {}", e)),
+        })
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateCollectionRequest) -> Result<Option<Collection>> {
+        sqlx::query_as::<_, Collection>(
+            r#"
+            UPDATE collections
+            SET title = COALESCE($2, title),
+                handle = COALESCE($3, handle),
+                description = COALESCE($4, description),
+                seo_title = COALESCE($5, seo_title),
+                seo_description = COALESCE($6, seo_description),
+                rules = COALESCE($7, rules),
+                disjunctive = COALESCE($8, disjunctive),
+                sort_order = COALESCE($9, sort_order),
+                published_at = CASE
+                    WHEN $10 IS NULL THEN published_at
+                    WHEN $10 THEN COALESCE(published_at, NOW())
+                END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.title.as_deref().map(str::trim))
+        .bind(request.handle.as_deref())
+        .bind(&request.description)
+        .bind(&request.seo_title)
+        .bind(&request.seo_description)
+        .bind(request.rules.as_ref().map(Json))
+        .bind(request.disjunctive)
+        .bind(request.sort_order.as_deref())
+        .bind(request.published)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A collection with this handle already exists")
+            }
+            e => Error::Other(format!("Failed to update collection: {}", e)),
+        })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM collections WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete collection: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn add_products(&self, collection_id: Uuid, product_ids: &[Uuid]) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO collection_products (collection_id, product_id, position)
+            SELECT $1, ids.product_id,
+                   COALESCE((SELECT MAX(position) FROM collection_products WHERE collection_id = $1), 0) + ids.n
+            FROM UNNEST($2::UUID[]) WITH ORDINALITY AS ids(product_id, n)
+            WHERE EXISTS (SELECT 1 FROM products WHERE id = ids.product_id)
+            ON CONFLICT (collection_id, product_id) DO NOTHING
+            "#
+        )
+        .bind(collection_id)
+        .bind(product_ids)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to add products to collection: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn remove_product(&self, collection_id: Uuid, product_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM collection_products WHERE collection_id = $1 AND product_id = $2")
+            .bind(collection_id)
+            .bind(product_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to remove product from collection: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
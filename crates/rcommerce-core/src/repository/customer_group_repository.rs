@@ -0,0 +1,231 @@
+//! Customer group repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        CreateCustomerGroupRequest, CustomerGroup, CustomerGroupPrice, SetCustomerGroupPriceRequest,
+        UpdateCustomerGroupRequest,
+    },
+};
+
+/// Repository trait for customer groups and their prices
+#[async_trait]
+pub trait CustomerGroupRepository: Send + Sync {
+    async fn create(&self, request: &CreateCustomerGroupRequest) -> Result<CustomerGroup>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<CustomerGroup>>;
+
+    /// Groups by name
+    async fn list(&self) -> Result<Vec<CustomerGroup>>;
+
+    async fn update(&self, id: Uuid, request: &UpdateCustomerGroupRequest) -> Result<Option<CustomerGroup>>;
+
+    /// Delete a group (its members leave it); false if there was no such group
+    async fn delete(&self, id: Uuid) -> Result<bool>;
+
+    /// Set (insert or replace) a group's price for a product or variant
+    /// tier; None if the product (or the variant of it) does not exist
+    async fn set_price(&self, customer_group_id: Uuid, request: &SetCustomerGroupPriceRequest, min_quantity: i32) -> Result<Option<CustomerGroupPrice>>;
+
+    /// Remove a price from a group; false if it was not there
+    async fn delete_price(&self, customer_group_id: Uuid, price_id: Uuid) -> Result<bool>;
+
+    /// A group's prices
+    async fn prices(&self, customer_group_id: Uuid) -> Result<Vec<CustomerGroupPrice>>;
+
+    /// A group's prices for some products
+    async fn prices_for_products(&self, customer_group_id: Uuid, product_ids: &[Uuid]) -> Result<Vec<CustomerGroupPrice>>;
+
+    /// The active group a customer belongs to
+    async fn group_of_customer(&self, customer_id: Uuid) -> Result<Option<CustomerGroup>>;
+
+    /// Move a customer into a group, or out of theirs; false if there was no
+    /// such customer
+    async fn assign(&self, customer_id: Uuid, customer_group_id: Option<Uuid>) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of CustomerGroupRepository
+pub struct PostgresCustomerGroupRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresCustomerGroupRepository {
+    /// Create a new PostgreSQL customer group repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CustomerGroupRepository for PostgresCustomerGroupRepository {
+    async fn create(&self, request: &CreateCustomerGroupRequest) -> Result<CustomerGroup> {
+        sqlx::query_as::<_, CustomerGroup>(
+            r#"
+            INSERT INTO customer_groups (name, description, discount_percent)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#
+        )
+        .bind(request.name.trim())
+        .bind(&request.description)
+        .bind(request.discount_percent)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation(format!("A customer group named {} already exists", request.name.trim()))
+            }
+            e => Error::Other(format!("Failed to create customer group: {}", e)),
+        })
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<CustomerGroup>> {
+        sqlx::query_as::<_, CustomerGroup>("SELECT * FROM customer_groups WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get customer group: {}", e)))
+    }
+
+    async fn list(&self) -> Result<Vec<CustomerGroup>> {
+        sqlx::query_as::<_, CustomerGroup>("SELECT * FROM customer_groups ORDER BY name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list customer groups: {}", e)))
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateCustomerGroupRequest) -> Result<Option<CustomerGroup>> {
+        sqlx::query_as::<_, CustomerGroup>(
+            r#"
+            UPDATE customer_groups
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                discount_percent = COALESCE($4, discount_percent),
+                is_active = COALESCE($5, is_active)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.name.as_deref().map(str::trim))
+        .bind(&request.description)
+        .bind(request.discount_percent)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A customer group with this name already exists")
+            }
+            e => Error::Other(format!("Failed to update customer group: {}", e)),
+        })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM customer_groups WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete customer group: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_price(&self, customer_group_id: Uuid, request: &SetCustomerGroupPriceRequest, min_quantity: i32) -> Result<Option<CustomerGroupPrice>> {
+        // Product-wide and variant prices have separate partial unique indexes
+        let conflict = if request.variant_id.is_some() {
+            "(customer_group_id, product_id, variant_id, min_quantity) WHERE variant_id IS NOT NULL"
+        } else {
+            "(customer_group_id, product_id, min_quantity) WHERE variant_id IS NULL"
+        };
+
+        sqlx::query_as::<_, CustomerGroupPrice>(&format!(
+            r#"
+            INSERT INTO customer_group_prices (customer_group_id, product_id, variant_id, min_quantity, price)
+            SELECT $1, p.id, $3, $4, $5
+            FROM products p
+            WHERE p.id = $2
+              AND ($3::uuid IS NULL OR EXISTS (
+                  SELECT 1 FROM product_variants v WHERE v.id = $3 AND v.product_id = p.id
+              ))
+            ON CONFLICT {} DO UPDATE
+            SET price = EXCLUDED.price
+            RETURNING *
+            "#,
+            conflict
+        ))
+        .bind(customer_group_id)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(min_quantity)
+        .bind(request.price)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to set customer group price: {}", e)))
+    }
+
+    async fn delete_price(&self, customer_group_id: Uuid, price_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM customer_group_prices WHERE id = $1 AND customer_group_id = $2")
+            .bind(price_id)
+            .bind(customer_group_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete customer group price: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn prices(&self, customer_group_id: Uuid) -> Result<Vec<CustomerGroupPrice>> {
+        sqlx::query_as::<_, CustomerGroupPrice>(
+            r#"
+            SELECT * FROM customer_group_prices
+            WHERE customer_group_id = $1
+            ORDER BY product_id, variant_id NULLS FIRST, min_quantity
+            "#
+        )
+        .bind(customer_group_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get customer group prices: {}", e)))
+    }
+
+    async fn prices_for_products(&self, customer_group_id: Uuid, product_ids: &[Uuid]) -> Result<Vec<CustomerGroupPrice>> {
+        sqlx::query_as::<_, CustomerGroupPrice>(
+            "SELECT * FROM customer_group_prices WHERE customer_group_id = $1 AND product_id = ANY($2)"
+        )
+        .bind(customer_group_id)
+        .bind(product_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get customer group prices: {}", e)))
+    }
+
+    async fn group_of_customer(&self, customer_id: Uuid) -> Result<Option<CustomerGroup>> {
+        sqlx::query_as::<_, CustomerGroup>(
+            r#"
+            SELECT g.*
+            FROM customer_groups g
+            JOIN customers c ON c.customer_group_id = g.id
+            WHERE c.id = $1 AND g.is_active
+            "#
+        )
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get customer group: {}", e)))
+    }
+
+    async fn assign(&self, customer_id: Uuid, customer_group_id: Option<Uuid>) -> Result<bool> {
+        let result = sqlx::query("UPDATE customers SET customer_group_id = $2, updated_at = NOW() WHERE id = $1")
+            .bind(customer_id)
+            .bind(customer_group_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to assign customer group: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
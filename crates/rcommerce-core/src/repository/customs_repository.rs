@@ -0,0 +1,92 @@
+//! Customs repository
+//!
+//! HS codes and countries of origin of products.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{ProductCustoms, SetProductCustomsRequest},
+};
+
+/// Repository trait for product customs data
+#[async_trait]
+pub trait CustomsRepository: Send + Sync {
+    /// Customs data of a product, if set
+    async fn find(&self, product_id: Uuid) -> Result<Option<ProductCustoms>>;
+
+    /// Customs data of those of `product_ids` that have it
+    async fn find_many(&self, product_ids: &[Uuid]) -> Result<Vec<ProductCustoms>>;
+
+    /// Set the customs data of a product
+    async fn upsert(&self, product_id: Uuid, request: &SetProductCustomsRequest) -> Result<ProductCustoms>;
+
+    /// Clear the customs data of a product; false if it had none
+    async fn delete(&self, product_id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of CustomsRepository
+pub struct PostgresCustomsRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresCustomsRepository {
+    /// Create a new PostgreSQL customs repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CustomsRepository for PostgresCustomsRepository {
+    async fn find(&self, product_id: Uuid) -> Result<Option<ProductCustoms>> {
+        sqlx::query_as::<_, ProductCustoms>("SELECT * FROM product_customs WHERE product_id = $1")
+            .bind(product_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get product customs data: {}", e)))
+    }
+
+    async fn find_many(&self, product_ids: &[Uuid]) -> Result<Vec<ProductCustoms>> {
+        sqlx::query_as::<_, ProductCustoms>("SELECT * FROM product_customs WHERE product_id = ANY($1)")
+            .bind(product_ids)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list product customs data: {}", e)))
+    }
+
+    async fn upsert(&self, product_id: Uuid, request: &SetProductCustomsRequest) -> Result<ProductCustoms> {
+        sqlx::query_as::<_, ProductCustoms>(
+            r#"
+            INSERT INTO product_customs (product_id, hs_code, origin_country, description)
+            VALUES ($1, $2, UPPER($3), $4)
+            ON CONFLICT (product_id) DO UPDATE
+            SET hs_code = EXCLUDED.hs_code,
+                origin_country = EXCLUDED.origin_country,
+                description = EXCLUDED.description,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(product_id)
+        .bind(&request.hs_code)
+        .bind(&request.origin_country)
+        .bind(&request.description)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => Error::not_found("Product not found"),
+            e => Error::Other(format!("Failed to save product customs data: {}", e)),
+        })
+    }
+
+    async fn delete(&self, product_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM product_customs WHERE product_id = $1")
+            .bind(product_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete product customs data: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,94 @@
+//! Delivery preferences repository
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    shipping::{DeliveryDetails, DeliveryWindow},
+};
+
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    delivery_instructions: Option<String>,
+    delivery_date: Option<NaiveDate>,
+    delivery_window_start: Option<NaiveTime>,
+    delivery_window_end: Option<NaiveTime>,
+}
+
+impl From<DeliveryRow> for DeliveryDetails {
+    fn from(row: DeliveryRow) -> Self {
+        Self {
+            instructions: row.delivery_instructions,
+            window: row.delivery_date.map(|date| DeliveryWindow {
+                date,
+                start: row.delivery_window_start,
+                end: row.delivery_window_end,
+            }),
+        }
+    }
+}
+
+/// Repository trait for order delivery preferences
+#[async_trait]
+pub trait DeliveryRepository: Send + Sync {
+    /// Record the delivery instructions and window chosen for an order
+    async fn save_for_order(&self, order_id: Uuid, delivery: &DeliveryDetails) -> Result<()>;
+
+    /// An order's delivery preferences, if the order exists
+    async fn for_order(&self, order_id: Uuid) -> Result<Option<DeliveryDetails>>;
+}
+
+/// PostgreSQL implementation of DeliveryRepository
+pub struct PostgresDeliveryRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresDeliveryRepository {
+    /// Create a new PostgreSQL delivery repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DeliveryRepository for PostgresDeliveryRepository {
+    async fn save_for_order(&self, order_id: Uuid, delivery: &DeliveryDetails) -> Result<()> {
+        let window = delivery.window.as_ref();
+        sqlx::query(
+            r#"
+            UPDATE orders
+            SET delivery_instructions = $2,
+                delivery_date = $3,
+                delivery_window_start = $4,
+                delivery_window_end = $5
+            WHERE id = $1
+            "#
+        )
+        .bind(order_id)
+        .bind(&delivery.instructions)
+        .bind(window.map(|w| w.date))
+        .bind(window.and_then(|w| w.start))
+        .bind(window.and_then(|w| w.end))
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save delivery preferences: {}", e)))?;
+        Ok(())
+    }
+
+    async fn for_order(&self, order_id: Uuid) -> Result<Option<DeliveryDetails>> {
+        let row = sqlx::query_as::<_, DeliveryRow>(
+            r#"
+            SELECT delivery_instructions, delivery_date, delivery_window_start, delivery_window_end
+            FROM orders
+            WHERE id = $1
+            "#
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get delivery preferences: {}", e)))?;
+        Ok(row.map(DeliveryDetails::from))
+    }
+}
@@ -0,0 +1,132 @@
+//! Exchange rate repository for historical rate storage
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{Currency, ExchangeRate, ExchangeRateFilter, RecordExchangeRateRequest},
+};
+
+/// Repository trait for exchange rate operations
+#[async_trait]
+pub trait ExchangeRateRepository: Send + Sync {
+    /// Record a new rate (rates are append-only)
+    async fn record(&self, request: RecordExchangeRateRequest) -> Result<ExchangeRate>;
+
+    /// Get the rate that was in effect for a currency pair at a point in time
+    async fn rate_at(&self, base: Currency, quote: Currency, at: DateTime<Utc>) -> Result<Option<ExchangeRate>>;
+
+    /// List rate history, newest first
+    async fn history(&self, filter: &ExchangeRateFilter, limit: i64, offset: i64) -> Result<Vec<ExchangeRate>>;
+
+    /// Set the rate snapshot on an order explicitly (e.g. to correct a missing rate)
+    async fn snapshot_order(&self, order_id: Uuid, rate: &ExchangeRate) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of ExchangeRateRepository
+pub struct PostgresExchangeRateRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresExchangeRateRepository {
+    /// Create a new PostgreSQL exchange rate repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateRepository for PostgresExchangeRateRepository {
+    async fn record(&self, request: RecordExchangeRateRequest) -> Result<ExchangeRate> {
+        if request.rate <= rust_decimal::Decimal::ZERO {
+            return Err(Error::validation("Exchange rate must be positive"));
+        }
+        if request.base_currency == request.quote_currency {
+            return Err(Error::validation("Base and quote currency must differ"));
+        }
+
+        let rate = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            INSERT INTO exchange_rates (base_currency, quote_currency, rate, source, effective_at)
+            VALUES ($1, $2, $3, $4, COALESCE($5, NOW()))
+            RETURNING *
+            "#
+        )
+        .bind(request.base_currency)
+        .bind(request.quote_currency)
+        .bind(request.rate)
+        .bind(&request.source)
+        .bind(request.effective_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record exchange rate: {}", e)))?;
+
+        Ok(rate)
+    }
+
+    async fn rate_at(&self, base: Currency, quote: Currency, at: DateTime<Utc>) -> Result<Option<ExchangeRate>> {
+        let rate = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            SELECT * FROM exchange_rates
+            WHERE base_currency = $1 AND quote_currency = $2 AND effective_at <= $3
+            ORDER BY effective_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(base)
+        .bind(quote)
+        .bind(at)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch exchange rate: {}", e)))?;
+
+        Ok(rate)
+    }
+
+    async fn history(&self, filter: &ExchangeRateFilter, limit: i64, offset: i64) -> Result<Vec<ExchangeRate>> {
+        let rates = sqlx::query_as::<_, ExchangeRate>(
+            r#"
+            SELECT * FROM exchange_rates
+            WHERE ($1::currency IS NULL OR base_currency = $1)
+            AND ($2::currency IS NULL OR quote_currency = $2)
+            AND ($3::timestamptz IS NULL OR effective_at >= $3)
+            AND ($4::timestamptz IS NULL OR effective_at <= $4)
+            ORDER BY effective_at DESC
+            LIMIT $5 OFFSET $6
+            "#
+        )
+        .bind(filter.base_currency)
+        .bind(filter.quote_currency)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list exchange rates: {}", e)))?;
+
+        Ok(rates)
+    }
+
+    async fn snapshot_order(&self, order_id: Uuid, rate: &ExchangeRate) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE orders
+            SET base_currency = $2, exchange_rate = $3, exchange_rate_at = $4, updated_at = NOW()
+            WHERE id = $1 AND currency = $5
+            "#
+        )
+        .bind(order_id)
+        .bind(rate.base_currency)
+        .bind(rate.rate)
+        .bind(rate.effective_at)
+        .bind(rate.quote_currency)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to snapshot order exchange rate: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
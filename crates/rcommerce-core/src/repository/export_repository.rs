@@ -0,0 +1,110 @@
+//! Export repository
+//!
+//! Streams rows for data exports straight from the database, one JSON object
+//! per row with the keys in `ExportEntity::columns`. Amounts are rendered as
+//! text so they keep their scale.
+
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::types::Json;
+
+use crate::{
+    Result, Error,
+    models::{ExportEntity, ExportFilter},
+};
+
+const PRODUCTS_SQL: &str = r#"
+    SELECT row_to_json(t) FROM (
+        SELECT p.id, p.title, p.slug, p.sku, p.product_type, p.price::text AS price,
+               p.compare_at_price::text AS compare_at_price, p.cost_price::text AS cost_price,
+               p.currency, p.inventory_quantity, p.inventory_policy, p.is_active, p.is_featured,
+               p.requires_shipping, p.weight::text AS weight, p.weight_unit, p.created_at, p.updated_at
+        FROM products p
+        WHERE ($1::timestamptz IS NULL OR p.created_at >= $1)
+          AND ($2::timestamptz IS NULL OR p.created_at < $2)
+          AND ($3::text IS NULL OR (CASE WHEN p.is_active THEN 'active' ELSE 'inactive' END) = $3)
+        ORDER BY p.created_at, p.id
+    ) t
+"#;
+
+const CUSTOMERS_SQL: &str = r#"
+    SELECT row_to_json(t) FROM (
+        SELECT c.id, c.email, c.first_name, c.last_name, c.phone, c.accepts_marketing, c.currency,
+               c.is_verified, COALESCE(o.order_count, 0) AS order_count,
+               COALESCE(o.total_spent, 0)::text AS total_spent,
+               c.confirmed_at, c.last_login_at, c.created_at
+        FROM customers c
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) AS order_count, SUM(total) AS total_spent
+            FROM orders
+            WHERE customer_id = c.id AND status NOT IN ('cancelled', 'refunded')
+        ) o ON true
+        WHERE ($1::timestamptz IS NULL OR c.created_at >= $1)
+          AND ($2::timestamptz IS NULL OR c.created_at < $2)
+          AND ($3::text IS NULL OR (CASE WHEN c.is_verified THEN 'verified' ELSE 'unverified' END) = $3)
+        ORDER BY c.created_at, c.id
+    ) t
+"#;
+
+const ORDERS_SQL: &str = r#"
+    SELECT row_to_json(t) FROM (
+        SELECT o.id, o.order_number, o.customer_id, o.email, o.status, o.payment_status,
+               o.fulfillment_status, o.currency, o.subtotal::text AS subtotal,
+               o.discount_total::text AS discount_total, o.shipping_total::text AS shipping_total,
+               o.tax_total::text AS tax_total, o.total::text AS total,
+               COALESCE(gc.amount, 0)::text AS gift_card_total, gc.codes AS gift_cards,
+               (SELECT COALESCE(SUM(i.quantity), 0) FROM order_items i WHERE i.order_id = o.id) AS item_count,
+               o.coupon_code, o.shipping_address, o.custom_fields, o.notes,
+               o.created_at, o.completed_at, o.cancelled_at
+        FROM orders o
+        LEFT JOIN LATERAL (
+            SELECT -SUM(gt.amount) AS amount,
+                   string_agg(DISTINCT '****-****-****-' || right(g.code, 4), ' ')
+                       FILTER (WHERE gt.kind = 'redeem') AS codes
+            FROM gift_card_transactions gt
+            JOIN gift_cards g ON g.id = gt.gift_card_id
+            WHERE gt.order_id = o.id AND gt.kind IN ('redeem', 'reverse')
+        ) gc ON true
+        WHERE ($1::timestamptz IS NULL OR o.created_at >= $1)
+          AND ($2::timestamptz IS NULL OR o.created_at < $2)
+          AND ($3::text IS NULL OR o.status::text = $3)
+        ORDER BY o.created_at, o.id
+    ) t
+"#;
+
+/// Repository trait for data exports
+pub trait ExportRepository: Send + Sync {
+    /// Rows of `entity` matching `filter`, oldest first, without loading them all into memory
+    fn rows<'a>(&'a self, entity: ExportEntity, filter: &'a ExportFilter) -> BoxStream<'a, Result<serde_json::Value>>;
+}
+
+/// PostgreSQL implementation of ExportRepository
+pub struct PostgresExportRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresExportRepository {
+    /// Create a new PostgreSQL export repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl ExportRepository for PostgresExportRepository {
+    fn rows<'a>(&'a self, entity: ExportEntity, filter: &'a ExportFilter) -> BoxStream<'a, Result<serde_json::Value>> {
+        let sql = match entity {
+            ExportEntity::Products => PRODUCTS_SQL,
+            ExportEntity::Customers => CUSTOMERS_SQL,
+            ExportEntity::Orders => ORDERS_SQL,
+        };
+        sqlx::query_scalar::<_, Json<serde_json::Value>>(sql)
+            .bind(filter.since)
+            .bind(filter.until)
+            .bind(filter.status.as_deref())
+            .fetch(&self.db)
+            .map(move |row| {
+                row.map(|Json(row)| row)
+                    .map_err(|e| Error::Other(format!("Failed to export {}: {}", entity, e)))
+            })
+            .boxed()
+    }
+}
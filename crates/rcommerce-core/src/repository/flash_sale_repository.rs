@@ -0,0 +1,161 @@
+//! Flash sale repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{FlashSale, FlashSaleStatus},
+};
+
+/// A flash sale definition, ready to insert
+#[derive(Debug, Clone)]
+pub struct NewFlashSale {
+    pub name: String,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub stock_limit: i32,
+    pub per_customer_limit: i32,
+    pub max_active_tokens: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+}
+
+/// Repository trait for flash sale definitions
+#[async_trait]
+pub trait FlashSaleRepository: Send + Sync {
+    /// Insert a draft sale
+    async fn create(&self, sale: &NewFlashSale) -> Result<FlashSale>;
+
+    /// Get a sale
+    async fn find(&self, id: Uuid) -> Result<Option<FlashSale>>;
+
+    /// Sales by newest start, optionally with one status
+    async fn list(&self, status: Option<FlashSaleStatus>, limit: i64) -> Result<Vec<FlashSale>>;
+
+    /// Whether another live sale covers the same product or variant
+    async fn overlapping_live(&self, sale: &FlashSale) -> Result<bool>;
+
+    /// Move a sale from one status to another; None if it was not in `from`
+    async fn transition(&self, id: Uuid, from: FlashSaleStatus, to: FlashSaleStatus) -> Result<Option<FlashSale>>;
+
+    /// Close a live sale, recording what sold
+    async fn close(&self, id: Uuid, sold_quantity: i32) -> Result<Option<FlashSale>>;
+}
+
+/// PostgreSQL implementation of FlashSaleRepository
+pub struct PostgresFlashSaleRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresFlashSaleRepository {
+    /// Create a new PostgreSQL flash sale repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl FlashSaleRepository for PostgresFlashSaleRepository {
+    async fn create(&self, sale: &NewFlashSale) -> Result<FlashSale> {
+        sqlx::query_as::<_, FlashSale>(
+            r#"
+            INSERT INTO flash_sales (name, product_id, variant_id, stock_limit, per_customer_limit,
+                                     max_active_tokens, starts_at, ends_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(&sale.name)
+        .bind(sale.product_id)
+        .bind(sale.variant_id)
+        .bind(sale.stock_limit)
+        .bind(sale.per_customer_limit)
+        .bind(sale.max_active_tokens)
+        .bind(sale.starts_at)
+        .bind(sale.ends_at)
+        .bind(sale.created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+                Error::validation("Product or variant not found")
+            }
+            e => Error::Other(format!("Failed to create flash sale: {}", e)),
+        })
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<FlashSale>> {
+        sqlx::query_as::<_, FlashSale>("SELECT * FROM flash_sales WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get flash sale: {}", e)))
+    }
+
+    async fn list(&self, status: Option<FlashSaleStatus>, limit: i64) -> Result<Vec<FlashSale>> {
+        sqlx::query_as::<_, FlashSale>(
+            r#"
+            SELECT * FROM flash_sales
+            WHERE ($1::flash_sale_status IS NULL OR status = $1)
+            ORDER BY starts_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list flash sales: {}", e)))
+    }
+
+    async fn overlapping_live(&self, sale: &FlashSale) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM flash_sales
+                WHERE status = 'live'
+                  AND id <> $1
+                  AND product_id = $2
+                  AND (variant_id IS NULL OR $3::uuid IS NULL OR variant_id = $3)
+            )
+            "#
+        )
+        .bind(sale.id)
+        .bind(sale.product_id)
+        .bind(sale.variant_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to check flash sales: {}", e)))
+    }
+
+    async fn transition(&self, id: Uuid, from: FlashSaleStatus, to: FlashSaleStatus) -> Result<Option<FlashSale>> {
+        sqlx::query_as::<_, FlashSale>(
+            "UPDATE flash_sales SET status = $3 WHERE id = $1 AND status = $2 RETURNING *"
+        )
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update flash sale: {}", e)))
+    }
+
+    async fn close(&self, id: Uuid, sold_quantity: i32) -> Result<Option<FlashSale>> {
+        sqlx::query_as::<_, FlashSale>(
+            r#"
+            UPDATE flash_sales
+            SET status = 'closed', sold_quantity = $2
+            WHERE id = $1 AND status = 'live'
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(sold_quantity)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to close flash sale: {}", e)))
+    }
+}
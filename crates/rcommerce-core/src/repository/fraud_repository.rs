@@ -0,0 +1,207 @@
+//! Fraud screening repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{RiskAction, RiskAssessment, RiskHistory, RiskReviewItem, RiskReviewStatus},
+};
+
+/// Repository trait for risk assessments and the review queue
+#[async_trait]
+pub trait FraudRepository: Send + Sync {
+    /// Orders of the customer, email or IP address; `recent_orders` counts
+    /// those placed since `since`
+    async fn history(
+        &self,
+        order_id: Uuid,
+        customer_id: Option<Uuid>,
+        email: &str,
+        client_ip: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<RiskHistory>;
+
+    /// Save an assessment with the score on its order, holding the order
+    /// for review or cancelling it as the action says
+    async fn record(&self, assessment: &RiskAssessment) -> Result<()>;
+
+    async fn find(&self, order_id: Uuid) -> Result<Option<RiskAssessment>>;
+
+    /// Reviewed or held orders, oldest first
+    async fn review_queue(&self, status: RiskReviewStatus, limit: i64, offset: i64) -> Result<Vec<RiskReviewItem>>;
+
+    /// Approve (release) or reject (cancel) a held order; None if it isn't
+    /// waiting for review
+    async fn review(
+        &self,
+        order_id: Uuid,
+        approve: bool,
+        reviewed_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<RiskAssessment>>;
+}
+
+/// PostgreSQL implementation of FraudRepository
+pub struct PostgresFraudRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresFraudRepository {
+    /// Create a new PostgreSQL fraud repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl FraudRepository for PostgresFraudRepository {
+    async fn history(
+        &self,
+        order_id: Uuid,
+        customer_id: Option<Uuid>,
+        email: &str,
+        client_ip: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<RiskHistory> {
+        sqlx::query_as::<_, RiskHistory>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM orders o
+                 WHERE o.id <> $1 AND o.status <> 'cancelled' AND o.deleted_at IS NULL
+                   AND (o.customer_id = $2 OR LOWER(o.email) = LOWER($3))) AS previous_orders,
+                (SELECT COUNT(*) FROM orders o
+                 WHERE o.created_at >= $5
+                   AND (o.id = $1 OR o.customer_id = $2 OR LOWER(o.email) = LOWER($3)
+                        OR o.id IN (SELECT a.order_id FROM order_risk_assessments a
+                                    WHERE a.client_ip = $4 AND a.created_at >= $5))) AS recent_orders
+            "#
+        )
+        .bind(order_id)
+        .bind(customer_id)
+        .bind(email)
+        .bind(client_ip)
+        .bind(since)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order history: {}", e)))
+    }
+
+    async fn record(&self, assessment: &RiskAssessment) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO order_risk_assessments (order_id, score, action, signals, email, client_ip, review_status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(assessment.order_id)
+        .bind(assessment.score)
+        .bind(assessment.action)
+        .bind(sqlx::types::Json(&assessment.signals))
+        .bind(&assessment.email)
+        .bind(&assessment.client_ip)
+        .bind(assessment.review_status)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save risk assessment: {}", e)))?;
+
+        // Cancelling releases the order's stock reservations (see migration 065)
+        let status = match assessment.action {
+            RiskAction::Review => Some("on_hold"),
+            RiskAction::Cancel => Some("cancelled"),
+            RiskAction::Allow | RiskAction::Require3ds => None,
+        };
+        sqlx::query(
+            "UPDATE orders SET risk_score = $2, status = COALESCE($3::order_status, status), updated_at = NOW() WHERE id = $1"
+        )
+        .bind(assessment.order_id)
+        .bind(assessment.score)
+        .bind(status)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save order risk score: {}", e)))?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn find(&self, order_id: Uuid) -> Result<Option<RiskAssessment>> {
+        sqlx::query_as::<_, RiskAssessment>("SELECT * FROM order_risk_assessments WHERE order_id = $1")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get risk assessment: {}", e)))
+    }
+
+    async fn review_queue(&self, status: RiskReviewStatus, limit: i64, offset: i64) -> Result<Vec<RiskReviewItem>> {
+        sqlx::query_as::<_, RiskReviewItem>(
+            r#"
+            SELECT a.*, o.order_number, o.total, o.currency::text AS currency,
+                   o.payment_status::text AS payment_status
+            FROM order_risk_assessments a
+            JOIN orders o ON o.id = a.order_id
+            WHERE a.review_status = $1
+            ORDER BY a.created_at, a.order_id
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list the review queue: {}", e)))
+    }
+
+    async fn review(
+        &self,
+        order_id: Uuid,
+        approve: bool,
+        reviewed_by: Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<RiskAssessment>> {
+        let mut tx = self.db.begin().await?;
+        let assessment = sqlx::query_as::<_, RiskAssessment>(
+            r#"
+            UPDATE order_risk_assessments
+            SET review_status = $2, reviewed_by = $3, reviewed_at = NOW(), review_note = $4
+            WHERE order_id = $1 AND review_status = 'pending'
+            RETURNING *
+            "#
+        )
+        .bind(order_id)
+        .bind(if approve { RiskReviewStatus::Approved } else { RiskReviewStatus::Rejected })
+        .bind(reviewed_by)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to review order: {}", e)))?;
+        if assessment.is_none() {
+            return Ok(None);
+        }
+
+        // Approved orders carry on where payment left them
+        sqlx::query(
+            r#"
+            UPDATE orders
+            SET status = CASE
+                    WHEN NOT $2 THEN 'cancelled'
+                    WHEN payment_status = 'paid' THEN 'confirmed'
+                    ELSE 'pending'
+                END::order_status,
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'on_hold'
+            "#
+        )
+        .bind(order_id)
+        .bind(approve)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to release order: {}", e)))?;
+        tx.commit().await?;
+
+        Ok(assessment)
+    }
+}
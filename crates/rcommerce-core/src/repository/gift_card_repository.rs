@@ -0,0 +1,321 @@
+//! Gift card repository
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{GiftCard, GiftCardStatus, GiftCardTransaction, GiftCardTransactionKind, NewGiftCard},
+};
+
+/// Repository trait for gift cards and their balance history
+#[async_trait]
+pub trait GiftCardRepository: Send + Sync {
+    /// Insert a card with its issue transaction
+    async fn create(&self, card: &NewGiftCard) -> Result<GiftCard>;
+
+    /// Get a card
+    async fn find(&self, id: Uuid) -> Result<Option<GiftCard>>;
+
+    /// Get a card by its (normalized) code
+    async fn find_by_code(&self, code: &str) -> Result<Option<GiftCard>>;
+
+    /// Cards by newest, optionally with one status
+    async fn list(&self, status: Option<GiftCardStatus>, limit: i64) -> Result<Vec<GiftCard>>;
+
+    /// A card's balance changes, oldest first
+    async fn transactions(&self, id: Uuid) -> Result<Vec<GiftCardTransaction>>;
+
+    /// Spend `amount` on an order; None if the card is no longer active,
+    /// unexpired and holding that much
+    async fn redeem(&self, id: Uuid, amount: Decimal, order_id: Uuid) -> Result<Option<GiftCardTransaction>>;
+
+    /// Give back a redemption for an order whose checkout failed
+    async fn reverse(&self, id: Uuid, amount: Decimal, order_id: Uuid) -> Result<GiftCardTransaction>;
+
+    /// Add `amount` (negative to deduct) to the balance; None if the card
+    /// does not exist or the balance would go negative
+    async fn adjust(&self, id: Uuid, amount: Decimal, actor_id: Uuid, note: Option<&str>) -> Result<Option<GiftCard>>;
+
+    /// Enable or disable a card; None if it does not exist or has expired
+    async fn set_status(&self, id: Uuid, status: GiftCardStatus) -> Result<Option<GiftCard>>;
+
+    /// Expire active cards past their expiry date, writing off their balance;
+    /// returns how many expired
+    async fn expire_due(&self) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of GiftCardRepository
+pub struct PostgresGiftCardRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresGiftCardRepository {
+    /// Create a new PostgreSQL gift card repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn record_transaction(
+    conn: &mut sqlx::PgConnection,
+    gift_card_id: Uuid,
+    kind: GiftCardTransactionKind,
+    amount: Decimal,
+    balance_after: Decimal,
+    order_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<GiftCardTransaction> {
+    sqlx::query_as::<_, GiftCardTransaction>(
+        r#"
+        INSERT INTO gift_card_transactions (gift_card_id, kind, amount, balance_after, order_id, actor_id, note)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#
+    )
+    .bind(gift_card_id)
+    .bind(kind)
+    .bind(amount)
+    .bind(balance_after)
+    .bind(order_id)
+    .bind(actor_id)
+    .bind(note)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to record gift card transaction: {}", e)))
+}
+
+#[async_trait]
+impl GiftCardRepository for PostgresGiftCardRepository {
+    async fn create(&self, card: &NewGiftCard) -> Result<GiftCard> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let created = sqlx::query_as::<_, GiftCard>(
+            r#"
+            INSERT INTO gift_cards (code, currency, initial_balance, balance, expires_at,
+                                    recipient_email, recipient_name, message, note, issued_by)
+            VALUES ($1, $2, $3, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(&card.code)
+        .bind(&card.currency)
+        .bind(card.amount)
+        .bind(card.expires_at)
+        .bind(&card.recipient_email)
+        .bind(&card.recipient_name)
+        .bind(&card.message)
+        .bind(&card.note)
+        .bind(card.issued_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A gift card with that code already exists")
+            }
+            e => Error::Other(format!("Failed to create gift card: {}", e)),
+        })?;
+
+        record_transaction(
+            &mut tx,
+            created.id,
+            GiftCardTransactionKind::Issue,
+            created.balance,
+            created.balance,
+            None,
+            card.issued_by,
+            None,
+        )
+        .await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit gift card: {}", e)))?;
+        Ok(created)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<GiftCard>> {
+        sqlx::query_as::<_, GiftCard>("SELECT * FROM gift_cards WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get gift card: {}", e)))
+    }
+
+    async fn find_by_code(&self, code: &str) -> Result<Option<GiftCard>> {
+        sqlx::query_as::<_, GiftCard>("SELECT * FROM gift_cards WHERE code = $1")
+            .bind(code)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get gift card: {}", e)))
+    }
+
+    async fn list(&self, status: Option<GiftCardStatus>, limit: i64) -> Result<Vec<GiftCard>> {
+        sqlx::query_as::<_, GiftCard>(
+            r#"
+            SELECT * FROM gift_cards
+            WHERE ($1::gift_card_status IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list gift cards: {}", e)))
+    }
+
+    async fn transactions(&self, id: Uuid) -> Result<Vec<GiftCardTransaction>> {
+        sqlx::query_as::<_, GiftCardTransaction>(
+            "SELECT * FROM gift_card_transactions WHERE gift_card_id = $1 ORDER BY created_at, id"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list gift card transactions: {}", e)))
+    }
+
+    async fn redeem(&self, id: Uuid, amount: Decimal, order_id: Uuid) -> Result<Option<GiftCardTransaction>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let balance = sqlx::query_scalar::<_, Decimal>(
+            r#"
+            UPDATE gift_cards
+            SET balance = balance - $2
+            WHERE id = $1
+              AND status = 'active'
+              AND balance >= $2
+              AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING balance
+            "#
+        )
+        .bind(id)
+        .bind(amount)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to redeem gift card: {}", e)))?;
+
+        let Some(balance) = balance else {
+            return Ok(None);
+        };
+        let transaction = record_transaction(
+            &mut tx,
+            id,
+            GiftCardTransactionKind::Redeem,
+            -amount,
+            balance,
+            Some(order_id),
+            None,
+            None,
+        )
+        .await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit gift card redemption: {}", e)))?;
+        Ok(Some(transaction))
+    }
+
+    async fn reverse(&self, id: Uuid, amount: Decimal, order_id: Uuid) -> Result<GiftCardTransaction> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let balance = sqlx::query_scalar::<_, Decimal>(
+            "UPDATE gift_cards SET balance = balance + $2 WHERE id = $1 RETURNING balance"
+        )
+        .bind(id)
+        .bind(amount)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to reverse gift card redemption: {}", e)))?;
+
+        let transaction = record_transaction(
+            &mut tx,
+            id,
+            GiftCardTransactionKind::Reverse,
+            amount,
+            balance,
+            Some(order_id),
+            None,
+            Some("Checkout failed"),
+        )
+        .await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit gift card reversal: {}", e)))?;
+        Ok(transaction)
+    }
+
+    async fn adjust(&self, id: Uuid, amount: Decimal, actor_id: Uuid, note: Option<&str>) -> Result<Option<GiftCard>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let card = sqlx::query_as::<_, GiftCard>(
+            "UPDATE gift_cards SET balance = balance + $2 WHERE id = $1 AND balance + $2 >= 0 RETURNING *"
+        )
+        .bind(id)
+        .bind(amount)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to adjust gift card: {}", e)))?;
+
+        let Some(card) = card else {
+            return Ok(None);
+        };
+        record_transaction(
+            &mut tx,
+            id,
+            GiftCardTransactionKind::Adjust,
+            amount,
+            card.balance,
+            None,
+            Some(actor_id),
+            note,
+        )
+        .await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit gift card adjustment: {}", e)))?;
+        Ok(Some(card))
+    }
+
+    async fn set_status(&self, id: Uuid, status: GiftCardStatus) -> Result<Option<GiftCard>> {
+        sqlx::query_as::<_, GiftCard>(
+            "UPDATE gift_cards SET status = $2 WHERE id = $1 AND status <> 'expired' RETURNING *"
+        )
+        .bind(id)
+        .bind(status)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update gift card: {}", e)))
+    }
+
+    async fn expire_due(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            WITH due AS (
+                SELECT id, balance FROM gift_cards
+                WHERE status = 'active' AND expires_at <= NOW()
+                FOR UPDATE
+            ), expired AS (
+                UPDATE gift_cards g
+                SET status = 'expired', balance = 0
+                FROM due
+                WHERE g.id = due.id
+                RETURNING g.id, due.balance AS written_off
+            )
+            INSERT INTO gift_card_transactions (gift_card_id, kind, amount, balance_after, note)
+            SELECT id, 'expire', -written_off, 0, 'Expired'
+            FROM expired
+            "#
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to expire gift cards: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,221 @@
+//! Hosted checkout repository
+//!
+//! Checkout sessions and payment links per order, and the order and payment
+//! updates when one is paid.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::models::{HostedCheckout, HostedCheckoutStatus, NewHostedCheckout, Order};
+use crate::{Error, Result};
+
+/// Repository trait for hosted checkouts
+#[async_trait]
+pub trait HostedCheckoutRepository: Send + Sync {
+    async fn find_order(&self, order_id: Uuid) -> Result<Option<Order>>;
+
+    async fn create(&self, checkout: &NewHostedCheckout) -> Result<HostedCheckout>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<HostedCheckout>>;
+
+    /// The checkout with this session or payment link ID at the gateway
+    async fn find_by_provider(&self, gateway: &str, provider_id: &str) -> Result<Option<HostedCheckout>>;
+
+    /// An order's checkouts, newest first
+    async fn list_for_order(&self, order_id: Uuid) -> Result<Vec<HostedCheckout>>;
+
+    /// Close an open checkout as expired; None if it was not open
+    async fn expire(&self, id: Uuid) -> Result<Option<HostedCheckout>>;
+
+    /// Mark a checkout paid, along with its order, and record the payment;
+    /// None if it was already completed
+    async fn complete(&self, id: Uuid, payment_id: &str) -> Result<Option<HostedCheckout>>;
+
+    /// Mark a checkout's delayed payment failed, and the order's payment if
+    /// it is still pending; None if the checkout was not open
+    async fn fail(&self, id: Uuid, error: &str) -> Result<Option<HostedCheckout>>;
+}
+
+/// PostgreSQL implementation of HostedCheckoutRepository
+#[derive(Clone)]
+pub struct PostgresHostedCheckoutRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresHostedCheckoutRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HostedCheckoutRepository for PostgresHostedCheckoutRepository {
+    async fn find_order(&self, order_id: Uuid) -> Result<Option<Order>> {
+        sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))
+    }
+
+    async fn create(&self, checkout: &NewHostedCheckout) -> Result<HostedCheckout> {
+        sqlx::query_as::<_, HostedCheckout>(
+            r#"
+            INSERT INTO hosted_checkouts (
+                order_id, gateway, mode, provider_id, url, amount, currency,
+                success_url, cancel_url, expires_at, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING *
+            "#,
+        )
+        .bind(checkout.order_id)
+        .bind(&checkout.gateway)
+        .bind(checkout.mode)
+        .bind(&checkout.provider_id)
+        .bind(&checkout.url)
+        .bind(checkout.amount)
+        .bind(&checkout.currency)
+        .bind(&checkout.success_url)
+        .bind(&checkout.cancel_url)
+        .bind(checkout.expires_at)
+        .bind(checkout.created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create hosted checkout: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<HostedCheckout>> {
+        sqlx::query_as::<_, HostedCheckout>("SELECT * FROM hosted_checkouts WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to fetch hosted checkout: {}", e)))
+    }
+
+    async fn find_by_provider(&self, gateway: &str, provider_id: &str) -> Result<Option<HostedCheckout>> {
+        sqlx::query_as::<_, HostedCheckout>(
+            "SELECT * FROM hosted_checkouts WHERE gateway = $1 AND provider_id = $2",
+        )
+        .bind(gateway)
+        .bind(provider_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch hosted checkout: {}", e)))
+    }
+
+    async fn list_for_order(&self, order_id: Uuid) -> Result<Vec<HostedCheckout>> {
+        sqlx::query_as::<_, HostedCheckout>(
+            "SELECT * FROM hosted_checkouts WHERE order_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list hosted checkouts: {}", e)))
+    }
+
+    async fn expire(&self, id: Uuid) -> Result<Option<HostedCheckout>> {
+        sqlx::query_as::<_, HostedCheckout>(
+            "UPDATE hosted_checkouts SET status = 'expired' WHERE id = $1 AND status = 'open' RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to expire hosted checkout: {}", e)))
+    }
+
+    async fn complete(&self, id: Uuid, payment_id: &str) -> Result<Option<HostedCheckout>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        // A payment can still arrive for a session closed here, so anything
+        // not yet completed can complete
+        let checkout = sqlx::query_as::<_, HostedCheckout>(
+            r#"
+            UPDATE hosted_checkouts
+            SET status = 'completed', payment_id = $2, error = NULL, completed_at = NOW()
+            WHERE id = $1 AND status <> 'completed'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(payment_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to complete hosted checkout: {}", e)))?;
+
+        let Some(checkout) = checkout else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE orders
+            SET payment_status = 'paid',
+                status = CASE WHEN status = 'pending' THEN 'confirmed'::order_status ELSE status END,
+                payment_method = COALESCE(payment_method, $2),
+                updated_at = NOW()
+            WHERE id = $1 AND payment_status <> 'paid'
+            "#,
+        )
+        .bind(checkout.order_id)
+        .bind(&checkout.gateway)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark order paid: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO payments (order_id, amount, currency, status, gateway, gateway_payment_id, processed_at)
+            VALUES ($1, $2, $3::currency, 'paid', $4, $5, NOW())
+            ON CONFLICT (gateway, gateway_payment_id) WHERE gateway_payment_id IS NOT NULL
+            DO UPDATE SET status = 'paid', processed_at = COALESCE(payments.processed_at, EXCLUDED.processed_at)
+            "#,
+        )
+        .bind(checkout.order_id)
+        .bind(checkout.amount)
+        .bind(&checkout.currency)
+        .bind(&checkout.gateway)
+        .bind(payment_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record payment: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(Some(checkout))
+    }
+
+    async fn fail(&self, id: Uuid, error: &str) -> Result<Option<HostedCheckout>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let checkout = sqlx::query_as::<_, HostedCheckout>(
+            "UPDATE hosted_checkouts SET status = $2, error = $3 WHERE id = $1 AND status = 'open' RETURNING *",
+        )
+        .bind(id)
+        .bind(HostedCheckoutStatus::Failed)
+        .bind(error)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update hosted checkout: {}", e)))?;
+
+        let Some(checkout) = checkout else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE orders SET payment_status = 'failed', updated_at = NOW() WHERE id = $1 AND payment_status = 'pending'",
+        )
+        .bind(checkout.order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update order payment: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(Some(checkout))
+    }
+}
@@ -0,0 +1,155 @@
+//! Idempotency key repository
+//!
+//! Claiming keys for a request, storing the response it produced, and
+//! purging keys past their retention.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{IdempotencyRecord, StoredResponse},
+};
+
+/// A request claiming an idempotency key
+#[derive(Debug, Clone)]
+pub struct IdempotencyClaim<'a> {
+    pub scope: &'a str,
+    pub key: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub request_hash: &'a str,
+    pub expires_at: DateTime<Utc>,
+    /// An unfinished claim on the same request locked before this is abandoned
+    pub stale_before: DateTime<Utc>,
+}
+
+/// Repository trait for idempotency keys
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Claim a key that is unused, expired, or abandoned by the same request;
+    /// returns the new lock id, or None if someone else holds the key
+    async fn claim(&self, claim: &IdempotencyClaim<'_>) -> Result<Option<Uuid>>;
+
+    /// Get a key
+    async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>>;
+
+    /// Store the response for a key still held by `lock_id`; false if the lock was lost
+    async fn complete(&self, scope: &str, key: &str, lock_id: Uuid, response: &StoredResponse) -> Result<bool>;
+
+    /// Drop a key held by `lock_id` so the request can be retried
+    async fn release(&self, scope: &str, key: &str, lock_id: Uuid) -> Result<bool>;
+
+    /// Delete keys that expired before `now`; returns how many
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of IdempotencyRepository
+pub struct PostgresIdempotencyRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresIdempotencyRepository {
+    /// Create a new PostgreSQL idempotency repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for PostgresIdempotencyRepository {
+    async fn claim(&self, claim: &IdempotencyClaim<'_>) -> Result<Option<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO idempotency_keys (scope, idempotency_key, method, path, request_hash, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (scope, idempotency_key) DO UPDATE
+            SET method = EXCLUDED.method,
+                path = EXCLUDED.path,
+                request_hash = EXCLUDED.request_hash,
+                status = 'in_progress',
+                lock_id = gen_random_uuid(),
+                response_status = NULL,
+                response_content_type = NULL,
+                response_body = NULL,
+                created_at = NOW(),
+                locked_at = NOW(),
+                completed_at = NULL,
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at <= NOW()
+               OR (idempotency_keys.status = 'in_progress'
+                   AND idempotency_keys.locked_at < $7
+                   AND idempotency_keys.request_hash = EXCLUDED.request_hash)
+            RETURNING lock_id
+            "#
+        )
+        .bind(claim.scope)
+        .bind(claim.key)
+        .bind(claim.method)
+        .bind(claim.path)
+        .bind(claim.request_hash)
+        .bind(claim.expires_at)
+        .bind(claim.stale_before)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to claim idempotency key: {}", e)))
+    }
+
+    async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        sqlx::query_as::<_, IdempotencyRecord>(
+            "SELECT * FROM idempotency_keys WHERE scope = $1 AND idempotency_key = $2"
+        )
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get idempotency key: {}", e)))
+    }
+
+    async fn complete(&self, scope: &str, key: &str, lock_id: Uuid, response: &StoredResponse) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET status = 'completed', response_status = $4, response_content_type = $5,
+                response_body = $6, completed_at = NOW()
+            WHERE scope = $1 AND idempotency_key = $2 AND lock_id = $3 AND status = 'in_progress'
+            "#
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(lock_id)
+        .bind(i32::from(response.status))
+        .bind(&response.content_type)
+        .bind(&response.body)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to store idempotent response: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release(&self, scope: &str, key: &str, lock_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE scope = $1 AND idempotency_key = $2 AND lock_id = $3 AND status = 'in_progress'
+            "#
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(lock_id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to release idempotency key: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge idempotency keys: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,135 @@
+//! In-app notification repository
+//!
+//! Every query is scoped to the recipient, so one customer can never read or
+//! mark another's notifications.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    notification::{InAppNotification, NewInAppNotification},
+};
+
+/// Repository trait for in-app notifications
+#[async_trait]
+pub trait InAppNotificationRepository: Send + Sync {
+    /// Add a notification to the recipient's bell
+    async fn create(&self, notification: &NewInAppNotification) -> Result<InAppNotification>;
+
+    /// Get one of the customer's notifications
+    async fn get(&self, customer_id: Uuid, id: Uuid) -> Result<Option<InAppNotification>>;
+
+    /// The customer's notifications, newest first
+    async fn list(&self, customer_id: Uuid, unread_only: bool, limit: i64, offset: i64) -> Result<Vec<InAppNotification>>;
+
+    /// How many of the customer's notifications are unread
+    async fn unread_count(&self, customer_id: Uuid) -> Result<i64>;
+
+    /// Mark one notification read; `None` if the customer has no such notification
+    async fn mark_read(&self, customer_id: Uuid, id: Uuid) -> Result<Option<InAppNotification>>;
+
+    /// Mark all the customer's notifications read; returns how many were unread
+    async fn mark_all_read(&self, customer_id: Uuid) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of InAppNotificationRepository
+pub struct PostgresInAppNotificationRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresInAppNotificationRepository {
+    /// Create a new PostgreSQL in-app notification repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl InAppNotificationRepository for PostgresInAppNotificationRepository {
+    async fn create(&self, notification: &NewInAppNotification) -> Result<InAppNotification> {
+        notification.validate()?;
+        sqlx::query_as::<_, InAppNotification>(
+            r#"
+            INSERT INTO in_app_notifications (customer_id, notification_id, category, title, body, link, data)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(notification.customer_id)
+        .bind(notification.notification_id)
+        .bind(notification.category.as_str())
+        .bind(&notification.title)
+        .bind(&notification.body)
+        .bind(&notification.link)
+        .bind(&notification.data)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create in-app notification: {}", e)))
+    }
+
+    async fn get(&self, customer_id: Uuid, id: Uuid) -> Result<Option<InAppNotification>> {
+        sqlx::query_as::<_, InAppNotification>(
+            "SELECT * FROM in_app_notifications WHERE id = $1 AND customer_id = $2"
+        )
+        .bind(id)
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get in-app notification: {}", e)))
+    }
+
+    async fn list(&self, customer_id: Uuid, unread_only: bool, limit: i64, offset: i64) -> Result<Vec<InAppNotification>> {
+        sqlx::query_as::<_, InAppNotification>(
+            r#"
+            SELECT * FROM in_app_notifications
+            WHERE customer_id = $1 AND (NOT $2 OR read_at IS NULL)
+            ORDER BY created_at DESC, id
+            LIMIT $3 OFFSET $4
+            "#
+        )
+        .bind(customer_id)
+        .bind(unread_only)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list in-app notifications: {}", e)))
+    }
+
+    async fn unread_count(&self, customer_id: Uuid) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM in_app_notifications WHERE customer_id = $1 AND read_at IS NULL"
+        )
+        .bind(customer_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to count unread in-app notifications: {}", e)))
+    }
+
+    async fn mark_read(&self, customer_id: Uuid, id: Uuid) -> Result<Option<InAppNotification>> {
+        sqlx::query_as::<_, InAppNotification>(
+            r#"
+            UPDATE in_app_notifications SET read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND customer_id = $2
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark in-app notification read: {}", e)))
+    }
+
+    async fn mark_all_read(&self, customer_id: Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE in_app_notifications SET read_at = NOW() WHERE customer_id = $1 AND read_at IS NULL"
+        )
+        .bind(customer_id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark in-app notifications read: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,193 @@
+//! Invoice repository for customer billing documents
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{Invoice, InvoiceSummary, Order, OrderItem, PaymentReceipt, RecordPaymentReceipt},
+    repository::{OrderArchiveRepository, PostgresOrderArchiveRepository},
+};
+
+/// Repository trait for invoice and receipt operations
+#[async_trait]
+pub trait InvoiceRepository: Send + Sync {
+    /// List invoices for a customer's orders, newest first
+    async fn list_for_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<InvoiceSummary>>;
+
+    /// Get the invoice for an order, only if it belongs to the customer
+    async fn find_for_customer(&self, customer_id: Uuid, order_id: Uuid) -> Result<Option<Invoice>>;
+
+    /// List payment receipts across a customer's orders, newest first
+    async fn receipts_for_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<PaymentReceipt>>;
+
+    /// Record a successful gateway payment and its receipt URL
+    async fn record_payment(&self, payment: RecordPaymentReceipt) -> Result<()>;
+
+    /// Attach a receipt URL to a payment once a gateway action completes
+    async fn set_receipt_url(&self, gateway_payment_id: &str, receipt_url: &str) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of InvoiceRepository
+pub struct PostgresInvoiceRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresInvoiceRepository {
+    /// Create a new PostgreSQL invoice repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl PostgresInvoiceRepository {
+    /// Read through to the order archive for orders moved out of `orders`
+    async fn find_archived_for_customer(&self, customer_id: Uuid, order_id: Uuid) -> Result<Option<Invoice>> {
+        let archive = PostgresOrderArchiveRepository::new(self.db.clone());
+        let order = match archive.find_order(order_id).await? {
+            Some(order) if order.customer_id == Some(customer_id) && !order.draft => order,
+            _ => return Ok(None),
+        };
+        let items = archive.find_items(order_id).await?;
+        let receipts = archive.find_receipts(order_id).await?;
+
+        Ok(Some(Invoice::new(order, items, receipts)))
+    }
+}
+
+#[async_trait]
+impl InvoiceRepository for PostgresInvoiceRepository {
+    async fn list_for_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<InvoiceSummary>> {
+        let invoices = sqlx::query_as::<_, InvoiceSummary>(
+            r#"
+            SELECT
+                o.id AS order_id,
+                o.order_number,
+                o.currency,
+                o.subtotal,
+                o.tax_total,
+                o.total,
+                o.payment_status,
+                o.created_at AS issued_at,
+                COUNT(p.id) FILTER (WHERE p.receipt_url IS NOT NULL) AS receipt_count
+            FROM orders o
+            LEFT JOIN payments p ON p.order_id = o.id
+            WHERE o.customer_id = $1 AND o.draft = false
+            GROUP BY o.id
+            ORDER BY o.created_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(customer_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list invoices: {}", e)))?;
+
+        Ok(invoices)
+    }
+
+    async fn find_for_customer(&self, customer_id: Uuid, order_id: Uuid) -> Result<Option<Invoice>> {
+        let order = sqlx::query_as::<_, Order>(
+            "SELECT * FROM orders WHERE id = $1 AND customer_id = $2 AND draft = false"
+        )
+        .bind(order_id)
+        .bind(customer_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))?;
+
+        let Some(order) = order else {
+            return self.find_archived_for_customer(customer_id, order_id).await;
+        };
+
+        let items = sqlx::query_as::<_, OrderItem>(
+            "SELECT * FROM order_items WHERE order_id = $1 ORDER BY created_at"
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch order items: {}", e)))?;
+
+        let receipts = sqlx::query_as::<_, PaymentReceipt>(
+            r#"
+            SELECT id AS payment_id, order_id, gateway, gateway_payment_id, amount, currency,
+                   status, receipt_url, processed_at
+            FROM payments
+            WHERE order_id = $1
+            ORDER BY created_at
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch payments: {}", e)))?;
+
+        Ok(Some(Invoice::new(order, items, receipts)))
+    }
+
+    async fn receipts_for_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<PaymentReceipt>> {
+        let receipts = sqlx::query_as::<_, PaymentReceipt>(
+            r#"
+            SELECT p.id AS payment_id, p.order_id, p.gateway, p.gateway_payment_id, p.amount,
+                   p.currency, p.status, p.receipt_url, p.processed_at
+            FROM payments p
+            JOIN orders o ON o.id = p.order_id
+            WHERE o.customer_id = $1 AND p.receipt_url IS NOT NULL
+            ORDER BY p.created_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(customer_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list receipts: {}", e)))?;
+
+        Ok(receipts)
+    }
+
+    async fn record_payment(&self, payment: RecordPaymentReceipt) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO payments (order_id, amount, currency, status, gateway, gateway_payment_id, receipt_url, processed_at)
+            VALUES ($1, $2, $3::currency, 'paid', $4, $5, $6, NOW())
+            ON CONFLICT (gateway, gateway_payment_id) WHERE gateway_payment_id IS NOT NULL
+            DO UPDATE SET
+                status = 'paid',
+                receipt_url = COALESCE(EXCLUDED.receipt_url, payments.receipt_url),
+                processed_at = COALESCE(payments.processed_at, EXCLUDED.processed_at)
+            "#
+        )
+        .bind(payment.order_id)
+        .bind(payment.amount)
+        .bind(payment.currency.to_uppercase())
+        .bind(&payment.gateway)
+        .bind(&payment.gateway_payment_id)
+        .bind(&payment.receipt_url)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record payment: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn set_receipt_url(&self, gateway_payment_id: &str, receipt_url: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE payments
+            SET receipt_url = $2, status = 'paid', processed_at = COALESCE(processed_at, NOW())
+            WHERE gateway_payment_id = $1
+            "#
+        )
+        .bind(gateway_payment_id)
+        .bind(receipt_url)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update payment receipt: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
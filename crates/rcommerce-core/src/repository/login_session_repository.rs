@@ -0,0 +1,193 @@
+//! Login session repository
+//!
+//! Starting sessions, rotating their refresh tokens and revoking them.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{LoginDevice, LoginSession},
+};
+
+/// Repository trait for login sessions
+#[async_trait]
+pub trait LoginSessionRepository: Send + Sync {
+    /// Start a session holding `token_hash`
+    async fn create(
+        &self,
+        customer_id: Uuid,
+        token_hash: &str,
+        device: &LoginDevice,
+        expires_at: DateTime<Utc>,
+    ) -> Result<LoginSession>;
+
+    /// The session whose current or previous refresh token has this hash
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<LoginSession>>;
+
+    /// A session by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<LoginSession>>;
+
+    /// Replace the refresh token if it is still `current_hash` and the session
+    /// is not revoked; None if another refresh got there first
+    async fn rotate(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Option<LoginSession>>;
+
+    /// Revoke one of a customer's sessions; false if there is no such active session
+    async fn revoke(&self, id: Uuid, customer_id: Uuid, reason: &str) -> Result<bool>;
+
+    /// Revoke every active session of a customer; returns how many
+    async fn revoke_all(&self, customer_id: Uuid, reason: &str) -> Result<u64>;
+
+    /// Sessions of a customer that are neither revoked nor expired, newest first
+    async fn list_active(&self, customer_id: Uuid, now: DateTime<Utc>) -> Result<Vec<LoginSession>>;
+
+    /// Delete sessions that expired or were revoked before `before`; returns how many
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of LoginSessionRepository
+pub struct PostgresLoginSessionRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresLoginSessionRepository {
+    /// Create a new PostgreSQL login session repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl LoginSessionRepository for PostgresLoginSessionRepository {
+    async fn create(
+        &self,
+        customer_id: Uuid,
+        token_hash: &str,
+        device: &LoginDevice,
+        expires_at: DateTime<Utc>,
+    ) -> Result<LoginSession> {
+        sqlx::query_as::<_, LoginSession>(
+            r#"
+            INSERT INTO login_sessions (customer_id, refresh_token_hash, user_agent, ip_address, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(customer_id)
+        .bind(token_hash)
+        .bind(&device.user_agent)
+        .bind(&device.ip_address)
+        .bind(expires_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create login session: {}", e)))
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<LoginSession>> {
+        sqlx::query_as::<_, LoginSession>(
+            "SELECT * FROM login_sessions WHERE refresh_token_hash = $1 OR previous_token_hash = $1 LIMIT 1"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get login session: {}", e)))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<LoginSession>> {
+        sqlx::query_as::<_, LoginSession>("SELECT * FROM login_sessions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to find login session: {}", e)))
+    }
+
+    async fn rotate(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Option<LoginSession>> {
+        sqlx::query_as::<_, LoginSession>(
+            r#"
+            UPDATE login_sessions
+            SET previous_token_hash = refresh_token_hash, refresh_token_hash = $3,
+                last_used_at = $4, expires_at = $5
+            WHERE id = $1 AND refresh_token_hash = $2 AND revoked_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(current_hash)
+        .bind(new_hash)
+        .bind(now)
+        .bind(expires_at)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to rotate refresh token: {}", e)))
+    }
+
+    async fn revoke(&self, id: Uuid, customer_id: Uuid, reason: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE login_sessions SET revoked_at = NOW(), revoked_reason = $3
+            WHERE id = $1 AND customer_id = $2 AND revoked_at IS NULL
+            "#
+        )
+        .bind(id)
+        .bind(customer_id)
+        .bind(reason)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to revoke login session: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn revoke_all(&self, customer_id: Uuid, reason: &str) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE login_sessions SET revoked_at = NOW(), revoked_reason = $2
+            WHERE customer_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            "#
+        )
+        .bind(customer_id)
+        .bind(reason)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to revoke login sessions: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn list_active(&self, customer_id: Uuid, now: DateTime<Utc>) -> Result<Vec<LoginSession>> {
+        sqlx::query_as::<_, LoginSession>(
+            r#"
+            SELECT * FROM login_sessions
+            WHERE customer_id = $1 AND revoked_at IS NULL AND expires_at > $2
+            ORDER BY last_used_at DESC
+            "#
+        )
+        .bind(customer_id)
+        .bind(now)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list login sessions: {}", e)))
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM login_sessions WHERE expires_at < $1 OR revoked_at < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge login sessions: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,444 @@
+//! Marketplace repository
+//!
+//! Marketplace listings and their push state, order import progress, and
+//! imported orders. Importing an order writes the order, its items, the
+//! stock it takes and the marketplace order id in one transaction; the
+//! stock change queues pushes of every listing of the items sold.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::{
+    CreateMarketplaceListingRequest, FulfillmentStatus, MarketplaceChannel, MarketplaceListing, MarketplaceOrder,
+    MarketplaceOrderImport, OrderStatus, PaymentStatus, PendingListing, UpdateMarketplaceListingRequest,
+};
+use crate::order::OrderNumbers;
+use crate::{Error, Result};
+
+/// Repository trait for marketplace listings and imported orders
+#[async_trait]
+pub trait MarketplaceRepository: Send + Sync {
+    /// Insert a listing
+    async fn create_listing(&self, request: &CreateMarketplaceListingRequest) -> Result<MarketplaceListing>;
+
+    async fn find_listing(&self, id: Uuid) -> Result<Option<MarketplaceListing>>;
+
+    /// Listings by marketplace and SKU, optionally for one marketplace or product
+    async fn list_listings(&self, channel: Option<MarketplaceChannel>, product_id: Option<Uuid>) -> Result<Vec<MarketplaceListing>>;
+
+    /// Listings of `channel` with the given marketplace SKUs
+    async fn listings_by_sku(&self, channel: MarketplaceChannel, skus: &[String]) -> Result<Vec<MarketplaceListing>>;
+
+    /// Change a listing and queue a push; None if it does not exist
+    async fn update_listing(&self, id: Uuid, request: &UpdateMarketplaceListingRequest) -> Result<Option<MarketplaceListing>>;
+
+    /// Delete a listing; false if it did not exist
+    async fn delete_listing(&self, id: Uuid) -> Result<bool>;
+
+    /// Queue a push of a listing; None if it does not exist
+    async fn queue_listing(&self, id: Uuid) -> Result<Option<MarketplaceListing>>;
+
+    /// Active listings of `channel` that changed since their last push,
+    /// oldest change first and failing ones last
+    async fn pending_listings(&self, channel: MarketplaceChannel, limit: i64) -> Result<Vec<PendingListing>>;
+
+    /// Record a push of the listing as read at `changed_at`; the listing
+    /// stays pending if it changed again meanwhile
+    async fn mark_synced(
+        &self,
+        id: Uuid,
+        changed_at: DateTime<Utc>,
+        price: Option<rust_decimal::Decimal>,
+        quantity: Option<i32>,
+    ) -> Result<()>;
+
+    /// Record a failed push; the listing stays pending
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<()>;
+
+    /// Time up to which orders of `channel` were imported
+    async fn orders_synced_until(&self, channel: MarketplaceChannel) -> Result<Option<DateTime<Utc>>>;
+
+    async fn set_orders_synced_until(&self, channel: MarketplaceChannel, until: DateTime<Utc>) -> Result<()>;
+
+    /// Import an order; None if the marketplace order was imported before
+    async fn import_order(&self, import: &MarketplaceOrderImport) -> Result<Option<MarketplaceOrder>>;
+
+    /// Imported orders, newest first
+    async fn list_orders(&self, channel: Option<MarketplaceChannel>, limit: i64, offset: i64) -> Result<Vec<MarketplaceOrder>>;
+}
+
+/// PostgreSQL implementation of MarketplaceRepository
+#[derive(Clone)]
+pub struct PostgresMarketplaceRepository {
+    db: sqlx::PgPool,
+    order_numbers: Option<Arc<OrderNumbers>>,
+}
+
+impl PostgresMarketplaceRepository {
+    /// Create a new PostgreSQL marketplace repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db, order_numbers: None }
+    }
+
+    /// Number imported orders of channels with their own sequence in
+    /// `[order_numbers.channels]` from it, instead of by marketplace order id
+    pub fn with_order_numbers(mut self, order_numbers: Arc<OrderNumbers>) -> Self {
+        self.order_numbers = Some(order_numbers);
+        self
+    }
+}
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.is_unique_violation())
+}
+
+#[async_trait]
+impl MarketplaceRepository for PostgresMarketplaceRepository {
+    async fn create_listing(&self, request: &CreateMarketplaceListingRequest) -> Result<MarketplaceListing> {
+        // The variant must belong to the product
+        sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            INSERT INTO marketplace_listings (channel, marketplace_sku, product_id, variant_id,
+                                              price_adjustment_percent, stock_buffer, sync_price, sync_inventory)
+            SELECT $1, $2, p.id, $4, $5, $6, $7, $8
+            FROM products p
+            WHERE p.id = $3
+              AND ($4::uuid IS NULL OR EXISTS (
+                  SELECT 1 FROM product_variants v WHERE v.id = $4 AND v.product_id = p.id
+              ))
+            RETURNING *
+            "#
+        )
+        .bind(request.channel)
+        .bind(request.marketplace_sku.trim())
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(request.price_adjustment_percent)
+        .bind(request.stock_buffer)
+        .bind(request.sync_price)
+        .bind(request.sync_inventory)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::validation(format!(
+                    "{} SKU '{}' is already mapped",
+                    request.channel,
+                    request.marketplace_sku.trim()
+                ))
+            } else {
+                Error::Other(format!("Failed to create marketplace listing: {}", e))
+            }
+        })?
+        .ok_or_else(|| Error::validation("Product or variant not found"))
+    }
+
+    async fn find_listing(&self, id: Uuid) -> Result<Option<MarketplaceListing>> {
+        sqlx::query_as::<_, MarketplaceListing>("SELECT * FROM marketplace_listings WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get marketplace listing: {}", e)))
+    }
+
+    async fn list_listings(&self, channel: Option<MarketplaceChannel>, product_id: Option<Uuid>) -> Result<Vec<MarketplaceListing>> {
+        sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            SELECT * FROM marketplace_listings
+            WHERE ($1::marketplace_channel IS NULL OR channel = $1)
+              AND ($2::uuid IS NULL OR product_id = $2)
+            ORDER BY channel, marketplace_sku
+            "#
+        )
+        .bind(channel)
+        .bind(product_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list marketplace listings: {}", e)))
+    }
+
+    async fn listings_by_sku(&self, channel: MarketplaceChannel, skus: &[String]) -> Result<Vec<MarketplaceListing>> {
+        sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings WHERE channel = $1 AND marketplace_sku = ANY($2)"
+        )
+        .bind(channel)
+        .bind(skus)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get marketplace listings: {}", e)))
+    }
+
+    async fn update_listing(&self, id: Uuid, request: &UpdateMarketplaceListingRequest) -> Result<Option<MarketplaceListing>> {
+        sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            UPDATE marketplace_listings
+            SET price_adjustment_percent = COALESCE($2, price_adjustment_percent),
+                stock_buffer = COALESCE($3, stock_buffer),
+                sync_price = COALESCE($4, sync_price),
+                sync_inventory = COALESCE($5, sync_inventory),
+                active = COALESCE($6, active),
+                changed_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.price_adjustment_percent)
+        .bind(request.stock_buffer)
+        .bind(request.sync_price)
+        .bind(request.sync_inventory)
+        .bind(request.active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update marketplace listing: {}", e)))
+    }
+
+    async fn delete_listing(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM marketplace_listings WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete marketplace listing: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn queue_listing(&self, id: Uuid) -> Result<Option<MarketplaceListing>> {
+        sqlx::query_as::<_, MarketplaceListing>(
+            "UPDATE marketplace_listings SET changed_at = NOW(), updated_at = NOW() WHERE id = $1 RETURNING *"
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to queue marketplace listing: {}", e)))
+    }
+
+    async fn pending_listings(&self, channel: MarketplaceChannel, limit: i64) -> Result<Vec<PendingListing>> {
+        sqlx::query_as::<_, PendingListing>(
+            r#"
+            SELECT l.*,
+                   COALESCE(v.price, p.price) AS price,
+                   COALESCE(v.currency, p.currency) AS currency,
+                   COALESCE(v.inventory_quantity, p.inventory_quantity) AS inventory_quantity
+            FROM marketplace_listings l
+            JOIN products p ON p.id = l.product_id
+            LEFT JOIN product_variants v ON v.id = l.variant_id
+            WHERE l.channel = $1
+              AND l.active
+              AND (l.synced_at IS NULL OR l.changed_at > l.synced_at)
+            ORDER BY l.last_error IS NOT NULL, l.changed_at
+            LIMIT $2
+            "#
+        )
+        .bind(channel)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get pending marketplace listings: {}", e)))
+    }
+
+    async fn mark_synced(
+        &self,
+        id: Uuid,
+        changed_at: DateTime<Utc>,
+        price: Option<rust_decimal::Decimal>,
+        quantity: Option<i32>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_listings
+            SET synced_at = CASE WHEN changed_at = $2 THEN NOW() ELSE synced_at END,
+                last_synced_price = COALESCE($3, last_synced_price),
+                last_synced_quantity = COALESCE($4, last_synced_quantity),
+                last_error = NULL,
+                updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(changed_at)
+        .bind(price)
+        .bind(quantity)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record marketplace push: {}", e)))?;
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query("UPDATE marketplace_listings SET last_error = $2, updated_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record marketplace push: {}", e)))?;
+        Ok(())
+    }
+
+    async fn orders_synced_until(&self, channel: MarketplaceChannel) -> Result<Option<DateTime<Utc>>> {
+        sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT orders_synced_until FROM marketplace_sync_state WHERE channel = $1"
+        )
+        .bind(channel)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get marketplace sync state: {}", e)))
+    }
+
+    async fn set_orders_synced_until(&self, channel: MarketplaceChannel, until: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_sync_state (channel, orders_synced_until)
+            VALUES ($1, $2)
+            ON CONFLICT (channel) DO UPDATE
+            SET orders_synced_until = EXCLUDED.orders_synced_until, updated_at = NOW()
+            "#
+        )
+        .bind(channel)
+        .bind(until)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save marketplace sync state: {}", e)))?;
+        Ok(())
+    }
+
+    async fn import_order(&self, import: &MarketplaceOrderImport) -> Result<Option<MarketplaceOrder>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to import marketplace order: {}", e)))?;
+
+        let status = if import.unmapped_skus.is_empty() { OrderStatus::Confirmed } else { OrderStatus::OnHold };
+        let metadata = serde_json::json!({
+            "marketplace": {
+                "channel": import.channel,
+                "order_id": import.marketplace_order_id,
+            }
+        });
+
+        // A number derived from the marketplace order id makes a concurrent
+        // import of the same order fail here; a sequence number makes it
+        // stop at marketplace_orders below
+        let order_number = match &self.order_numbers {
+            Some(numbers) if numbers.config().channels.contains_key(import.channel.as_str()) => {
+                numbers.next(&mut tx, import.channel.as_str(), import.placed_at).await?
+            }
+            _ => import.order_number.clone(),
+        };
+        let inserted = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO orders (order_number, email, currency, subtotal, tax_total, shipping_total,
+                                discount_total, total, status, payment_status, fulfillment_status,
+                                shipping_address, payment_method, notes, tags, metadata, channel, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, 0, $7, $8, $9, $10, $11, $12, $13, ARRAY[$12], $14, $12, $15)
+            RETURNING id
+            "#
+        )
+        .bind(&order_number)
+        .bind(&import.email)
+        .bind(import.currency)
+        .bind(import.subtotal)
+        .bind(import.tax_total)
+        .bind(import.shipping_total)
+        .bind(import.total)
+        .bind(status)
+        .bind(PaymentStatus::Paid)
+        .bind(FulfillmentStatus::Pending)
+        .bind(&import.shipping_address)
+        .bind(import.channel.as_str())
+        .bind(&import.notes)
+        .bind(&metadata)
+        .bind(import.placed_at)
+        .fetch_one(&mut *tx)
+        .await;
+        let order_id = match inserted {
+            Ok(id) => id,
+            Err(ref e) if is_unique_violation(e) => return Ok(None),
+            Err(e) => return Err(Error::Other(format!("Failed to import marketplace order: {}", e))),
+        };
+
+        for line in &import.lines {
+            let subtotal = line.price * rust_decimal::Decimal::from(line.quantity);
+            sqlx::query(
+                r#"
+                INSERT INTO order_items (order_id, product_id, variant_id, title, sku, quantity,
+                                         price, subtotal, tax_amount, total)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#
+            )
+            .bind(order_id)
+            .bind(line.product_id)
+            .bind(line.variant_id)
+            .bind(&line.title)
+            .bind(&line.sku)
+            .bind(line.quantity)
+            .bind(line.price)
+            .bind(subtotal)
+            .bind(line.tax_amount)
+            .bind(subtotal + line.tax_amount)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to import marketplace order item: {}", e)))?;
+
+            let stock = match line.variant_id {
+                Some(variant_id) => sqlx::query(
+                    "UPDATE product_variants SET inventory_quantity = inventory_quantity - $2, updated_at = NOW() WHERE id = $1"
+                )
+                .bind(variant_id),
+                None => sqlx::query(
+                    "UPDATE products SET inventory_quantity = inventory_quantity - $2, updated_at = NOW() WHERE id = $1"
+                )
+                .bind(line.product_id),
+            };
+            stock
+                .bind(line.quantity)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to update stock for marketplace order: {}", e)))?;
+        }
+
+        let record = sqlx::query_as::<_, MarketplaceOrder>(
+            r#"
+            INSERT INTO marketplace_orders (channel, marketplace_order_id, order_id, unmapped_skus, placed_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (channel, marketplace_order_id) DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(import.channel)
+        .bind(&import.marketplace_order_id)
+        .bind(order_id)
+        .bind(&import.unmapped_skus)
+        .bind(import.placed_at)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to import marketplace order: {}", e)))?;
+
+        // Imported before under another order number
+        let Some(record) = record else {
+            return Ok(None);
+        };
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to import marketplace order: {}", e)))?;
+        Ok(Some(record))
+    }
+
+    async fn list_orders(&self, channel: Option<MarketplaceChannel>, limit: i64, offset: i64) -> Result<Vec<MarketplaceOrder>> {
+        sqlx::query_as::<_, MarketplaceOrder>(
+            r#"
+            SELECT * FROM marketplace_orders
+            WHERE ($1::marketplace_channel IS NULL OR channel = $1)
+            ORDER BY placed_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(channel)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list marketplace orders: {}", e)))
+    }
+}
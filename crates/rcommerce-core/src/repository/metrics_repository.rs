@@ -0,0 +1,349 @@
+//! Business metrics repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{AnomalyMetric, CustomerRole, Incident, IncidentStatus},
+    observability::{AnomalyCheck, BusinessMetrics, MetricAlertState, RateSample},
+};
+
+/// Repository trait for business metrics, alert state and incidents
+#[async_trait]
+pub trait MetricsRepository: Send + Sync {
+    /// Orders, payments and carts over the last `window_secs`
+    async fn business_metrics(&self, window_secs: u64) -> Result<BusinessMetrics>;
+
+    /// The state of every alert rule checked so far
+    async fn alert_states(&self) -> Result<Vec<MetricAlertState>>;
+
+    async fn save_alert_state(&self, state: &MetricAlertState) -> Result<()>;
+
+    /// Staff with the given roles (id, email) for alert emails
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>>;
+
+    /// Add an instance's request and 5xx counts to a minute
+    async fn add_request_stats(&self, minute: DateTime<Utc>, requests: i64, server_errors: i64) -> Result<()>;
+
+    /// Drop request counts from before a time
+    async fn purge_request_stats(&self, before: DateTime<Utc>) -> Result<u64>;
+
+    /// A rate over the `buckets` windows of `bucket_secs` up to now, newest
+    /// first (windows without events are zero)
+    async fn rate_series(&self, metric: AnomalyMetric, bucket_secs: u64, buckets: u32) -> Result<Vec<RateSample>>;
+
+    /// The unresolved incident for a metric
+    async fn unresolved_incident(&self, metric: AnomalyMetric) -> Result<Option<Incident>>;
+
+    async fn open_incident(&self, check: &AnomalyCheck) -> Result<Incident>;
+
+    /// Record the latest check of an unresolved incident
+    async fn update_incident(&self, id: Uuid, check: &AnomalyCheck) -> Result<()>;
+
+    /// Acknowledge an open incident; None if there is no open incident with the id
+    async fn acknowledge_incident(&self, id: Uuid, by: Uuid) -> Result<Option<Incident>>;
+
+    /// Resolve an unresolved incident (by staff, or None when the rate
+    /// recovered); None if there is no unresolved incident with the id
+    async fn resolve_incident(&self, id: Uuid, by: Option<Uuid>) -> Result<Option<Incident>>;
+
+    async fn find_incident(&self, id: Uuid) -> Result<Option<Incident>>;
+
+    /// Incidents, newest first
+    async fn list_incidents(&self, status: Option<IncidentStatus>, limit: i64, offset: i64) -> Result<Vec<Incident>>;
+}
+
+/// PostgreSQL implementation of MetricsRepository
+pub struct PostgresMetricsRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresMetricsRepository {
+    /// Create a new PostgreSQL metrics repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl MetricsRepository for PostgresMetricsRepository {
+    async fn business_metrics(&self, window_secs: u64) -> Result<BusinessMetrics> {
+        let since = Utc::now() - Duration::seconds(window_secs as i64);
+
+        let (carts_created, checkouts_started, orders_placed, orders_paid, payments_succeeded, payments_failed) =
+            sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64)>(
+                r#"
+                SELECT
+                    (SELECT COUNT(*) FROM carts WHERE created_at >= $1),
+                    (SELECT COUNT(*) FROM carts
+                     WHERE created_at >= $1 AND (email IS NOT NULL OR shipping_address_id IS NOT NULL)),
+                    (SELECT COUNT(*) FROM orders WHERE created_at >= $1),
+                    (SELECT COUNT(*) FROM orders WHERE created_at >= $1 AND payment_status = 'paid'),
+                    (SELECT COUNT(*) FROM payments WHERE updated_at >= $1 AND status IN ('authorized', 'paid')),
+                    (SELECT COUNT(*) FROM payments WHERE updated_at >= $1 AND status = 'failed')
+                "#
+            )
+            .bind(since)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to count business metrics: {}", e)))?;
+
+        Ok(BusinessMetrics {
+            window_secs,
+            carts_created,
+            checkouts_started,
+            orders_placed,
+            orders_paid,
+            payments_succeeded,
+            payments_failed,
+        })
+    }
+
+    async fn alert_states(&self) -> Result<Vec<MetricAlertState>> {
+        sqlx::query_as::<_, MetricAlertState>("SELECT * FROM metric_alert_states")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get alert states: {}", e)))
+    }
+
+    async fn save_alert_state(&self, state: &MetricAlertState) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO metric_alert_states
+                (rule_name, breached_since, firing, last_value, last_notified_at, checked_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (rule_name) DO UPDATE
+            SET breached_since = EXCLUDED.breached_since,
+                firing = EXCLUDED.firing,
+                last_value = EXCLUDED.last_value,
+                last_notified_at = EXCLUDED.last_notified_at,
+                checked_at = EXCLUDED.checked_at
+            "#
+        )
+        .bind(&state.rule_name)
+        .bind(state.breached_since)
+        .bind(state.firing)
+        .bind(state.last_value)
+        .bind(state.last_notified_at)
+        .bind(state.checked_at)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save alert state: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>> {
+        let roles: Vec<String> = roles
+            .iter()
+            .map(|role| format!("{:?}", role).to_lowercase())
+            .collect();
+
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, email FROM customers WHERE role::text = ANY($1) ORDER BY email")
+            .bind(&roles)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list staff: {}", e)))
+    }
+
+    async fn add_request_stats(&self, minute: DateTime<Utc>, requests: i64, server_errors: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO request_stats (minute, requests, server_errors)
+            VALUES (date_trunc('minute', $1), $2, $3)
+            ON CONFLICT (minute) DO UPDATE
+            SET requests = request_stats.requests + EXCLUDED.requests,
+                server_errors = request_stats.server_errors + EXCLUDED.server_errors
+            "#
+        )
+        .bind(minute)
+        .bind(requests)
+        .bind(server_errors)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save request stats: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn purge_request_stats(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM request_stats WHERE minute < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge request stats: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn rate_series(&self, metric: AnomalyMetric, bucket_secs: u64, buckets: u32) -> Result<Vec<RateSample>> {
+        let now = Utc::now();
+        let since = now - Duration::seconds((bucket_secs * buckets as u64) as i64);
+
+        // (window, events, total) with window 0 the latest
+        let query = match metric {
+            AnomalyMetric::PaymentFailureRate => r#"
+                SELECT FLOOR(EXTRACT(EPOCH FROM ($1 - updated_at)) / $2)::INTEGER AS window,
+                       COUNT(*) FILTER (WHERE status = 'failed'),
+                       COUNT(*)
+                FROM payments
+                WHERE updated_at > $3 AND updated_at <= $1
+                  AND status IN ('authorized', 'paid', 'failed')
+                GROUP BY 1
+            "#,
+            AnomalyMetric::RefundRate => r#"
+                SELECT window, SUM(refunds)::BIGINT, SUM(orders)::BIGINT
+                FROM (
+                    SELECT FLOOR(EXTRACT(EPOCH FROM ($1 - created_at)) / $2)::INTEGER AS window, 1 AS refunds, 0 AS orders
+                    FROM refunds WHERE created_at > $3 AND created_at <= $1
+                    UNION ALL
+                    SELECT FLOOR(EXTRACT(EPOCH FROM ($1 - created_at)) / $2)::INTEGER, 0, 1
+                    FROM orders WHERE created_at > $3 AND created_at <= $1
+                ) events
+                GROUP BY window
+            "#,
+            AnomalyMetric::ServerErrorRate => r#"
+                SELECT FLOOR(EXTRACT(EPOCH FROM ($1 - minute)) / $2)::INTEGER AS window,
+                       SUM(server_errors)::BIGINT,
+                       SUM(requests)::BIGINT
+                FROM request_stats
+                WHERE minute > $3 AND minute <= $1
+                GROUP BY 1
+            "#,
+        };
+
+        let rows = sqlx::query_as::<_, (i32, i64, i64)>(query)
+            .bind(now)
+            .bind(bucket_secs as f64)
+            .bind(since)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get {} history: {}", metric.as_str(), e)))?;
+
+        let mut series = vec![RateSample::default(); buckets as usize];
+        for (window, events, total) in rows {
+            if let Some(sample) = usize::try_from(window).ok().and_then(|window| series.get_mut(window)) {
+                *sample = RateSample { events, total };
+            }
+        }
+        Ok(series)
+    }
+
+    async fn unresolved_incident(&self, metric: AnomalyMetric) -> Result<Option<Incident>> {
+        sqlx::query_as::<_, Incident>("SELECT * FROM incidents WHERE metric = $1 AND status <> 'resolved'")
+            .bind(metric)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get incident: {}", e)))
+    }
+
+    async fn open_incident(&self, check: &AnomalyCheck) -> Result<Incident> {
+        sqlx::query_as::<_, Incident>(
+            r#"
+            INSERT INTO incidents
+                (metric, summary, rate, baseline_mean, baseline_stddev, z_score, peak_rate, peak_z_score, samples)
+            VALUES ($1, $2, $3, $4, $5, $6, $3, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(check.metric)
+        .bind(check.summary())
+        .bind(check.rate)
+        .bind(check.mean)
+        .bind(check.stddev)
+        .bind(check.z_score)
+        .bind(check.samples)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to open incident: {}", e)))
+    }
+
+    async fn update_incident(&self, id: Uuid, check: &AnomalyCheck) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE incidents
+            SET summary = $2,
+                rate = $3,
+                baseline_mean = $4,
+                baseline_stddev = $5,
+                z_score = $6,
+                peak_rate = GREATEST(peak_rate, $3),
+                peak_z_score = GREATEST(peak_z_score, $6),
+                samples = $7,
+                last_seen_at = NOW()
+            WHERE id = $1 AND status <> 'resolved'
+            "#
+        )
+        .bind(id)
+        .bind(check.summary())
+        .bind(check.rate)
+        .bind(check.mean)
+        .bind(check.stddev)
+        .bind(check.z_score)
+        .bind(check.samples)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update incident: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn acknowledge_incident(&self, id: Uuid, by: Uuid) -> Result<Option<Incident>> {
+        sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET status = 'acknowledged', acknowledged_at = NOW(), acknowledged_by = $2
+            WHERE id = $1 AND status = 'open'
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(by)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to acknowledge incident: {}", e)))
+    }
+
+    async fn resolve_incident(&self, id: Uuid, by: Option<Uuid>) -> Result<Option<Incident>> {
+        sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET status = 'resolved', resolved_at = NOW(), resolved_by = $2
+            WHERE id = $1 AND status <> 'resolved'
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(by)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve incident: {}", e)))
+    }
+
+    async fn find_incident(&self, id: Uuid) -> Result<Option<Incident>> {
+        sqlx::query_as::<_, Incident>("SELECT * FROM incidents WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get incident: {}", e)))
+    }
+
+    async fn list_incidents(&self, status: Option<IncidentStatus>, limit: i64, offset: i64) -> Result<Vec<Incident>> {
+        sqlx::query_as::<_, Incident>(
+            r#"
+            SELECT * FROM incidents
+            WHERE $1::incident_status IS NULL OR status = $1
+            ORDER BY opened_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list incidents: {}", e)))
+    }
+}
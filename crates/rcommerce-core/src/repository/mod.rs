@@ -10,19 +10,121 @@ pub mod cart_repository;
 pub mod coupon_repository;
 pub mod api_key_repository;
 pub mod subscription_repository;
+pub mod subscription_plan_repository;
+pub mod stock_adjustment_repository;
+pub mod purchase_order_repository;
+pub mod supplier_feed_repository;
+pub mod po_dispatch_repository;
+pub mod register_repository;
+pub mod price_list_repository;
+pub mod address_repository;
+pub mod addon_repository;
+pub mod delivery_repository;
+pub mod checkout_field_repository;
+pub mod gift_card_repository;
+pub mod flash_sale_repository;
+pub mod export_repository;
+pub mod purchase_limit_repository;
+pub mod idempotency_repository;
+pub mod login_session_repository;
+pub mod two_factor_repository;
+pub mod oauth_repository;
+pub mod access_denial_repository;
+pub mod access_log_repository;
+pub mod audit_repository;
+pub mod soft_delete_repository;
+pub mod secret_repository;
+pub mod scheduled_job_repository;
+pub mod marketplace_repository;
+pub mod automation_repository;
+pub mod report_repository;
+pub mod sales_report_repository;
+pub mod hosted_checkout_repository;
+pub mod catalog_repository;
+pub mod redirect_repository;
+pub mod customer_group_repository;
+pub mod attribute_repository;
+pub mod fraud_repository;
+pub mod payment_session_repository;
+pub mod payment_capture_repository;
+pub mod payout_repository;
+pub mod account_repository;
+pub mod metrics_repository;
+pub mod analytics_repository;
+pub mod return_repository;
+pub mod role_repository;
 pub mod statistics_repository;
 pub mod order_repository;
 pub mod inventory_repository;
 pub mod fulfillment_repository;
 pub mod notification_repository;
 pub mod category_repository;
+pub mod collection_repository;
+pub mod product_image_repository;
+pub mod shipment_repository;
+pub mod tracking_repository;
+pub mod pickup_repository;
+pub mod customs_repository;
+pub mod print_batch_repository;
 pub mod tag_repository;
+pub mod exchange_rate_repository;
+pub mod invoice_repository;
+pub mod notification_template_repository;
+pub mod in_app_notification_repository;
+pub mod price_history_repository;
+pub mod order_archive_repository;
+pub mod webhook_replay_repository;
+pub mod webhook_dispatch_repository;
+pub mod outbox_repository;
 
 // Re-export cart, coupon, api_key, subscription, statistics, order, inventory, and fulfillment traits
 pub use cart_repository::{CartRepository, PgCartRepository};
 pub use coupon_repository::{CouponRepository, PgCouponRepository};
-pub use api_key_repository::{ApiKeyRepository, ApiKeyRecord, CreateApiKeyRequest, PostgresApiKeyRepository};
+pub use api_key_repository::{ApiKeyRepository, ApiKeyRecord, ApiKeyType, CreateApiKeyRequest, PostgresApiKeyRepository, origin_matches, ip_matches, is_valid_ip_network};
 pub use subscription_repository::{SubscriptionRepository, PostgresSubscriptionRepository};
+pub use subscription_plan_repository::{SubscriptionPlanRepository, PostgresSubscriptionPlanRepository};
+pub use stock_adjustment_repository::{StockAdjustmentRepository, PostgresStockAdjustmentRepository};
+pub use purchase_order_repository::{PurchaseOrderRepository, PostgresPurchaseOrderRepository};
+pub use supplier_feed_repository::{SupplierFeedRepository, PostgresSupplierFeedRepository};
+pub use po_dispatch_repository::{PoDispatchRepository, PostgresPoDispatchRepository};
+pub use register_repository::{RegisterRepository, PostgresRegisterRepository};
+pub use price_list_repository::{PriceListRepository, PostgresPriceListRepository, BasePrice};
+pub use address_repository::{AddressRepository, PostgresAddressRepository, AddressCheck};
+pub use addon_repository::{AddonRepository, PostgresAddonRepository};
+pub use delivery_repository::{DeliveryRepository, PostgresDeliveryRepository};
+pub use checkout_field_repository::{CheckoutFieldRepository, PostgresCheckoutFieldRepository};
+pub use gift_card_repository::{GiftCardRepository, PostgresGiftCardRepository};
+pub use flash_sale_repository::{FlashSaleRepository, NewFlashSale, PostgresFlashSaleRepository};
+pub use export_repository::{ExportRepository, PostgresExportRepository};
+pub use purchase_limit_repository::{PurchaseLimitRepository, PostgresPurchaseLimitRepository};
+pub use idempotency_repository::{IdempotencyClaim, IdempotencyRepository, PostgresIdempotencyRepository};
+pub use login_session_repository::{LoginSessionRepository, PostgresLoginSessionRepository};
+pub use two_factor_repository::{TwoFactorRepository, PostgresTwoFactorRepository};
+pub use oauth_repository::{OAuthRepository, PostgresOAuthRepository};
+pub use access_denial_repository::{AccessDenialRepository, PostgresAccessDenialRepository};
+pub use access_log_repository::{AccessLogRepository, PostgresAccessLogRepository};
+pub use audit_repository::{AuditRepository, PostgresAuditRepository};
+pub use soft_delete_repository::{PostgresSoftDeleteRepository, SoftDeleteRepository};
+pub use secret_repository::{SecretKind, SecretRepository, StoredSecret, PostgresSecretRepository};
+pub use scheduled_job_repository::{ScheduledJobRepository, PostgresScheduledJobRepository};
+pub use marketplace_repository::{MarketplaceRepository, PostgresMarketplaceRepository};
+pub use automation_repository::{AutomationRepository, PostgresAutomationRepository};
+pub use report_repository::{ReportRepository, PostgresReportRepository};
+pub use sales_report_repository::{SalesReportRepository, PostgresSalesReportRepository};
+pub use hosted_checkout_repository::{HostedCheckoutRepository, PostgresHostedCheckoutRepository};
+pub use catalog_repository::{CatalogRepository, PostgresCatalogRepository};
+pub use redirect_repository::{RedirectRepository, PostgresRedirectRepository};
+pub use customer_group_repository::{CustomerGroupRepository, PostgresCustomerGroupRepository};
+pub use attribute_repository::{AttributeRepository, PostgresAttributeRepository};
+pub use fraud_repository::{FraudRepository, PostgresFraudRepository};
+pub use payment_session_repository::{PaymentSessionRepository, PostgresPaymentSessionRepository};
+pub use payment_capture_repository::{PaymentCaptureRepository, PostgresPaymentCaptureRepository};
+pub use payout_repository::{PayoutRepository, PostgresPayoutRepository};
+pub use account_repository::{AccountRepository, PostgresAccountRepository};
+pub use metrics_repository::{MetricsRepository, PostgresMetricsRepository};
+pub use analytics_repository::{AnalyticsRepository, PostgresAnalyticsRepository};
+pub use return_repository::{ReturnRepository, PostgresReturnRepository, OrderPayment};
+pub use role_repository::{RoleRepository, PostgresRoleRepository};
 pub use statistics_repository::{
     StatisticsRepository, PgStatisticsRepository, Period,
     SalesSummary, OrderStatistics, ProductPerformance, CustomerStatistics,
@@ -34,14 +136,35 @@ pub use inventory_repository::{InventoryRepository, PostgresInventoryRepository}
 pub use fulfillment_repository::{FulfillmentRepository, PostgresFulfillmentRepository};
 pub use notification_repository::{NotificationRepository, PostgresNotificationRepository};
 pub use category_repository::{CategoryRepository, CategoryTreeNode, PostgresCategoryRepository};
+pub use collection_repository::{CollectionRepository, PostgresCollectionRepository};
+pub use product_image_repository::{NewProductImage, ProductImageRepository, PostgresProductImageRepository};
+pub use shipment_repository::{ShipmentRepository, PostgresShipmentRepository};
+pub use tracking_repository::{TrackingRepository, PostgresTrackingRepository};
+pub use pickup_repository::{PickupRepository, PostgresPickupRepository};
+pub use customs_repository::{CustomsRepository, PostgresCustomsRepository};
+pub use print_batch_repository::{PrintBatchRepository, PostgresPrintBatchRepository};
 pub use tag_repository::{TagRepository, PostgresTagRepository};
+pub use exchange_rate_repository::{ExchangeRateRepository, PostgresExchangeRateRepository};
+pub use invoice_repository::{InvoiceRepository, PostgresInvoiceRepository};
+pub use notification_template_repository::{
+    NotificationTemplateRepository, NotificationTemplateRecord, SaveNotificationTemplateRequest,
+    PostgresNotificationTemplateRepository,
+};
+pub use in_app_notification_repository::{InAppNotificationRepository, PostgresInAppNotificationRepository};
+pub use price_history_repository::{PriceHistoryRepository, PostgresPriceHistoryRepository};
+pub use order_archive_repository::{OrderArchiveRepository, PostgresOrderArchiveRepository};
+pub use webhook_replay_repository::{
+    WebhookReplayRepository, PostgresWebhookReplayRepository, ReplayDelivery, WebhookTarget,
+};
+pub use webhook_dispatch_repository::{WebhookDispatchRepository, PostgresWebhookDispatchRepository};
+pub use outbox_repository::{enqueue_event, OutboxEntry, OutboxRepository, PostgresOutboxRepository, OUTBOX_CHANNEL};
 
 // PostgreSQL exports
 pub use postgres::{
     PostgresProductRepository as ProductRepository,
     PostgresCustomerRepository as CustomerRepository,
     PostgresDb as Database,
-    create_pool,
+    create_pool, create_replica_set, ReplicaSet, ReplicaStatus,
 };
 
 // Re-export traits
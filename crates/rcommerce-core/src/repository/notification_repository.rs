@@ -8,6 +8,7 @@ use crate::{
     Result, Error,
     notification::types::{Notification, DeliveryStatus},
     notification::NotificationChannel,
+    notification::channels::EmailEvent,
 };
 
 /// Repository trait for notification operations
@@ -63,6 +64,14 @@ pub trait NotificationRepository: Send + Sync {
     
     /// Clean up old delivered notifications
     async fn cleanup_old(&self, before: DateTime<Utc>) -> Result<u64>;
+    
+    /// Remember the provider's message id, so webhook events can be matched
+    async fn set_provider_message_id(&self, id: Uuid, provider: &str, message_id: &str) -> Result<()>;
+    
+    /// Apply a delivery status change reported by an email provider;
+    /// returns false when no notification matched or a complaint or
+    /// permanent bounce was already recorded
+    async fn record_provider_event(&self, event: &EmailEvent) -> Result<bool>;
 }
 
 /// PostgreSQL implementation of NotificationRepository
@@ -142,7 +151,7 @@ impl NotificationRepository for PostgresNotificationRepository {
                 metadata = $11,
                 scheduled_at = $12,
                 updated_at = $13
-            WHERE id = $14
+            WHERE id = $14 AND created_at = $15
             RETURNING *
             "#
         )
@@ -160,6 +169,8 @@ impl NotificationRepository for PostgresNotificationRepository {
         .bind(notification.scheduled_at)
         .bind(Utc::now())
         .bind(notification.id)
+        // Partition key, so only the notification's month is touched
+        .bind(notification.created_at)
         .fetch_one(&self.db)
         .await
         .map_err(|e| Error::Other(format!("Failed to update notification: {}", e)))?;
@@ -374,4 +385,51 @@ impl NotificationRepository for PostgresNotificationRepository {
         
         Ok(result.rows_affected())
     }
+    
+    async fn set_provider_message_id(&self, id: Uuid, provider: &str, message_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE notifications
+            SET metadata = COALESCE(NULLIF(metadata, 'null'::jsonb), '{}'::jsonb)
+                    || jsonb_build_object('provider', $1::text, 'provider_message_id', $2::text)
+            WHERE id = $3
+            "#
+        )
+        .bind(provider)
+        .bind(message_id)
+        .bind(id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record provider message id: {}", e)))?;
+        
+        Ok(())
+    }
+    
+    async fn record_provider_event(&self, event: &EmailEvent) -> Result<bool> {
+        if event.notification_id.is_none() && event.message_id.is_none() {
+            return Ok(false);
+        }
+        
+        // Complaints are final; a permanent bounce can only turn into one
+        let result = sqlx::query(
+            r#"
+            UPDATE notifications
+            SET status = $1,
+                error_message = COALESCE($2, error_message),
+                updated_at = NOW()
+            WHERE (id = $3 OR ($3 IS NULL AND metadata->>'provider_message_id' = $4))
+            AND status <> 'complained'
+            AND (status <> 'bounced' OR $1 = 'complained')
+            "#
+        )
+        .bind(event.status)
+        .bind(&event.detail)
+        .bind(event.notification_id)
+        .bind(&event.message_id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record email provider event: {}", e)))?;
+        
+        Ok(result.rows_affected() > 0)
+    }
 }
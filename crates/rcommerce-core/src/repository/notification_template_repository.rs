@@ -0,0 +1,130 @@
+//! Notification template repository for merchant-edited (DB-stored) templates
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    notification::{catalog, NotificationChannel},
+};
+
+/// A stored notification template
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct NotificationTemplateRecord {
+    pub id: Uuid,
+    /// Template type from the variables catalog (e.g. "order_confirmation")
+    pub name: String,
+    pub channel: NotificationChannel,
+    pub subject_template: String,
+    pub body_template: String,
+    pub html_template: Option<String>,
+    /// Placeholders the template uses
+    pub variables: serde_json::Value,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create or replace a stored template
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaveNotificationTemplateRequest {
+    pub subject_template: String,
+    pub body_template: String,
+    pub html_template: Option<String>,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Repository trait for stored notification templates
+#[async_trait]
+pub trait NotificationTemplateRepository: Send + Sync {
+    /// List all stored templates
+    async fn list(&self) -> Result<Vec<NotificationTemplateRecord>>;
+
+    /// Get a stored template by name
+    async fn get(&self, name: &str) -> Result<Option<NotificationTemplateRecord>>;
+
+    /// Create or replace a template, rejecting placeholders the template type doesn't provide
+    async fn save(&self, name: &str, request: SaveNotificationTemplateRequest) -> Result<NotificationTemplateRecord>;
+}
+
+/// PostgreSQL implementation of NotificationTemplateRepository
+pub struct PostgresNotificationTemplateRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresNotificationTemplateRepository {
+    /// Create a new PostgreSQL notification template repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NotificationTemplateRepository for PostgresNotificationTemplateRepository {
+    async fn list(&self) -> Result<Vec<NotificationTemplateRecord>> {
+        sqlx::query_as::<_, NotificationTemplateRecord>(
+            "SELECT * FROM notification_templates ORDER BY name"
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list notification templates: {}", e)))
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<NotificationTemplateRecord>> {
+        sqlx::query_as::<_, NotificationTemplateRecord>(
+            "SELECT * FROM notification_templates WHERE name = $1"
+        )
+        .bind(name)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get notification template: {}", e)))
+    }
+
+    async fn save(&self, name: &str, request: SaveNotificationTemplateRequest) -> Result<NotificationTemplateRecord> {
+        let parts = [
+            request.subject_template.as_str(),
+            request.body_template.as_str(),
+            request.html_template.as_deref().unwrap_or_default(),
+        ];
+        catalog::validate_template(name, &parts)?;
+
+        let mut variables: Vec<String> = Vec::new();
+        for variable in parts.iter().flat_map(|part| catalog::placeholders(part)) {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+
+        sqlx::query_as::<_, NotificationTemplateRecord>(
+            r#"
+            INSERT INTO notification_templates (name, channel, subject_template, body_template, html_template, variables, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (name) DO UPDATE SET
+                subject_template = EXCLUDED.subject_template,
+                body_template = EXCLUDED.body_template,
+                html_template = EXCLUDED.html_template,
+                variables = EXCLUDED.variables,
+                is_active = EXCLUDED.is_active,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(name)
+        .bind(NotificationChannel::Email)
+        .bind(&request.subject_template)
+        .bind(&request.body_template)
+        .bind(&request.html_template)
+        .bind(serde_json::json!(variables))
+        .bind(request.is_active)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save notification template: {}", e)))
+    }
+}
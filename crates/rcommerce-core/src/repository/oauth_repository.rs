@@ -0,0 +1,300 @@
+//! OAuth login repository
+//!
+//! Provider accounts linked to customers, and logins in flight.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{ExternalProfile, NewOAuthLogin, OAuthIdentity, OAuthLogin, OAuthProvider},
+};
+
+/// Repository trait for OAuth logins
+#[async_trait]
+pub trait OAuthRepository: Send + Sync {
+    async fn find_identity(&self, provider: OAuthProvider, subject: &str) -> Result<Option<OAuthIdentity>>;
+
+    async fn list_identities(&self, customer_id: Uuid) -> Result<Vec<OAuthIdentity>>;
+
+    /// Record a login with a linked identity, updating its email
+    async fn touch_identity(&self, id: Uuid, email: Option<&str>) -> Result<()>;
+
+    /// Link a provider account to a customer; marks the customer verified
+    /// when the provider verified their email
+    async fn link_identity(&self, customer_id: Uuid, profile: &ExternalProfile) -> Result<OAuthIdentity>;
+
+    /// Create a verified customer without a password, linked to the provider account
+    async fn create_customer(&self, profile: &ExternalProfile, first_name: &str, last_name: &str) -> Result<Uuid>;
+
+    /// Unlink a customer's identity; false if they have no such identity
+    async fn unlink_identity(&self, customer_id: Uuid, id: Uuid) -> Result<bool>;
+
+    /// Customer with this email, ignoring case
+    async fn find_customer_by_email(&self, email: &str) -> Result<Option<Uuid>>;
+
+    /// Whether the customer can log in with a password
+    async fn has_password(&self, customer_id: Uuid) -> Result<bool>;
+
+    /// Start a login redirecting to the provider
+    async fn create_login(&self, login: &NewOAuthLogin) -> Result<OAuthLogin>;
+
+    /// Take the login of a state on the provider's callback; None if the
+    /// state is unknown, expired or already used
+    async fn take_login(&self, state_hash: &str, now: DateTime<Utc>) -> Result<Option<OAuthLogin>>;
+
+    /// Attach the resolved customer and the login code to a login
+    async fn complete_login(
+        &self,
+        id: Uuid,
+        customer_id: Uuid,
+        created_customer: bool,
+        login_code_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()>;
+
+    /// Use up a login code; None if it is unknown, expired or already used
+    async fn consume_login_code(&self, login_code_hash: &str, now: DateTime<Utc>) -> Result<Option<OAuthLogin>>;
+
+    /// Delete logins that expired before `before`; returns how many
+    async fn purge_logins(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of OAuthRepository
+pub struct PostgresOAuthRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresOAuthRepository {
+    /// Create a new PostgreSQL OAuth repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+async fn insert_identity(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    customer_id: Uuid,
+    profile: &ExternalProfile,
+) -> Result<OAuthIdentity> {
+    sqlx::query_as::<_, OAuthIdentity>(
+        r#"
+        INSERT INTO oauth_identities (customer_id, provider, subject, email)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#
+    )
+    .bind(customer_id)
+    .bind(profile.provider.as_str())
+    .bind(&profile.subject)
+    .bind(&profile.email)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            Error::validation(format!("This {} account is already linked", profile.provider))
+        }
+        e => Error::Other(format!("Failed to link identity: {}", e)),
+    })
+}
+
+#[async_trait]
+impl OAuthRepository for PostgresOAuthRepository {
+    async fn find_identity(&self, provider: OAuthProvider, subject: &str) -> Result<Option<OAuthIdentity>> {
+        sqlx::query_as::<_, OAuthIdentity>("SELECT * FROM oauth_identities WHERE provider = $1 AND subject = $2")
+            .bind(provider.as_str())
+            .bind(subject)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get identity: {}", e)))
+    }
+
+    async fn list_identities(&self, customer_id: Uuid) -> Result<Vec<OAuthIdentity>> {
+        sqlx::query_as::<_, OAuthIdentity>(
+            "SELECT * FROM oauth_identities WHERE customer_id = $1 ORDER BY created_at"
+        )
+        .bind(customer_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list identities: {}", e)))
+    }
+
+    async fn touch_identity(&self, id: Uuid, email: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE oauth_identities SET last_login_at = NOW(), email = COALESCE($2, email) WHERE id = $1"
+        )
+        .bind(id)
+        .bind(email)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update identity: {}", e)))?;
+        Ok(())
+    }
+
+    async fn link_identity(&self, customer_id: Uuid, profile: &ExternalProfile) -> Result<OAuthIdentity> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+        let identity = insert_identity(&mut tx, customer_id, profile).await?;
+        if profile.email_verified {
+            sqlx::query(
+                r#"
+                UPDATE customers SET is_verified = true, confirmed_at = COALESCE(confirmed_at, NOW()), updated_at = NOW()
+                WHERE id = $1 AND NOT is_verified
+                "#
+            )
+            .bind(customer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to verify customer: {}", e)))?;
+        }
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(identity)
+    }
+
+    async fn create_customer(&self, profile: &ExternalProfile, first_name: &str, last_name: &str) -> Result<Uuid> {
+        let email = profile
+            .email
+            .as_deref()
+            .ok_or_else(|| Error::validation("The provider did not share an email address"))?;
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+        let customer_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO customers (email, first_name, last_name, is_verified, confirmed_at)
+            VALUES ($1, $2, $3, $4, CASE WHEN $4 THEN NOW() END)
+            RETURNING id
+            "#
+        )
+        .bind(email)
+        .bind(first_name)
+        .bind(last_name)
+        .bind(profile.email_verified)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => Error::validation("Email already exists"),
+            e => Error::Other(format!("Failed to create customer: {}", e)),
+        })?;
+        insert_identity(&mut tx, customer_id, profile).await?;
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(customer_id)
+    }
+
+    async fn unlink_identity(&self, customer_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM oauth_identities WHERE id = $1 AND customer_id = $2")
+            .bind(id)
+            .bind(customer_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to unlink identity: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_customer_by_email(&self, email: &str) -> Result<Option<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM customers WHERE LOWER(email) = LOWER($1) LIMIT 1")
+            .bind(email)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get customer: {}", e)))
+    }
+
+    async fn has_password(&self, customer_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT password_hash IS NOT NULL FROM customers WHERE id = $1")
+            .bind(customer_id)
+            .fetch_optional(&self.db)
+            .await
+            .map(|has| has.unwrap_or(false))
+            .map_err(|e| Error::Other(format!("Failed to get customer: {}", e)))
+    }
+
+    async fn create_login(&self, login: &NewOAuthLogin) -> Result<OAuthLogin> {
+        sqlx::query_as::<_, OAuthLogin>(
+            r#"
+            INSERT INTO oauth_logins (provider, state_hash, code_verifier, nonce, return_to, client_state, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(login.provider.as_str())
+        .bind(&login.state_hash)
+        .bind(&login.code_verifier)
+        .bind(&login.nonce)
+        .bind(&login.return_to)
+        .bind(&login.client_state)
+        .bind(login.expires_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create OAuth login: {}", e)))
+    }
+
+    async fn take_login(&self, state_hash: &str, now: DateTime<Utc>) -> Result<Option<OAuthLogin>> {
+        sqlx::query_as::<_, OAuthLogin>(
+            r#"
+            UPDATE oauth_logins SET callback_at = $2
+            WHERE state_hash = $1 AND callback_at IS NULL AND expires_at > $2
+            RETURNING *
+            "#
+        )
+        .bind(state_hash)
+        .bind(now)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get OAuth login: {}", e)))
+    }
+
+    async fn complete_login(
+        &self,
+        id: Uuid,
+        customer_id: Uuid,
+        created_customer: bool,
+        login_code_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE oauth_logins
+            SET customer_id = $2, created_customer = $3, login_code_hash = $4, login_code_expires_at = $5
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(customer_id)
+        .bind(created_customer)
+        .bind(login_code_hash)
+        .bind(expires_at)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to complete OAuth login: {}", e)))?;
+        Ok(())
+    }
+
+    async fn consume_login_code(&self, login_code_hash: &str, now: DateTime<Utc>) -> Result<Option<OAuthLogin>> {
+        sqlx::query_as::<_, OAuthLogin>(
+            r#"
+            UPDATE oauth_logins SET consumed_at = $2
+            WHERE login_code_hash = $1 AND consumed_at IS NULL AND login_code_expires_at > $2
+            RETURNING *
+            "#
+        )
+        .bind(login_code_hash)
+        .bind(now)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to use OAuth login code: {}", e)))
+    }
+
+    async fn purge_logins(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM oauth_logins WHERE expires_at < $1 AND (login_code_expires_at IS NULL OR login_code_expires_at < $1)"
+        )
+        .bind(before)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to purge OAuth logins: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,141 @@
+//! Order archive repository
+//!
+//! Archival itself runs in the `archive_orders` SQL function so each order
+//! is snapshotted and deleted in the same statement. Reads go through
+//! `jsonb_populate_record`, so archived orders decode into the same models
+//! as live ones.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{ArchivedOrder, Order, OrderArchiveStats, OrderItem, PaymentReceipt},
+};
+
+/// Repository trait for archived orders
+#[async_trait]
+pub trait OrderArchiveRepository: Send + Sync {
+    /// Move up to `limit` eligible orders finished before `cutoff` into the archive
+    async fn archive_batch(&self, cutoff: DateTime<Utc>, statuses: &[String], limit: i64) -> Result<Vec<ArchivedOrder>>;
+
+    /// Count orders that would be archived
+    async fn count_archivable(&self, cutoff: DateTime<Utc>, statuses: &[String]) -> Result<i64>;
+
+    /// Get an archived order with its snapshot
+    async fn find(&self, id: Uuid) -> Result<Option<ArchivedOrder>>;
+
+    /// Get an archived order as an order row
+    async fn find_order(&self, id: Uuid) -> Result<Option<Order>>;
+
+    /// Get an archived order's items
+    async fn find_items(&self, order_id: Uuid) -> Result<Vec<OrderItem>>;
+
+    /// Get an archived order's payment receipts
+    async fn find_receipts(&self, order_id: Uuid) -> Result<Vec<PaymentReceipt>>;
+
+    /// Archive size overview
+    async fn stats(&self) -> Result<OrderArchiveStats>;
+}
+
+/// PostgreSQL implementation of OrderArchiveRepository
+pub struct PostgresOrderArchiveRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresOrderArchiveRepository {
+    /// Create a new PostgreSQL order archive repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl OrderArchiveRepository for PostgresOrderArchiveRepository {
+    async fn archive_batch(&self, cutoff: DateTime<Utc>, statuses: &[String], limit: i64) -> Result<Vec<ArchivedOrder>> {
+        sqlx::query_as::<_, ArchivedOrder>("SELECT * FROM archive_orders($1, $2, $3)")
+            .bind(cutoff)
+            .bind(statuses)
+            .bind(limit as i32)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to archive orders: {}", e)))
+    }
+
+    async fn count_archivable(&self, cutoff: DateTime<Utc>, statuses: &[String]) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM archivable_orders($1, $2)")
+            .bind(cutoff)
+            .bind(statuses)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to count archivable orders: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<ArchivedOrder>> {
+        sqlx::query_as::<_, ArchivedOrder>("SELECT * FROM orders_archive WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to fetch archived order: {}", e)))
+    }
+
+    async fn find_order(&self, id: Uuid) -> Result<Option<Order>> {
+        sqlx::query_as::<_, Order>(
+            r#"
+            SELECT o.*
+            FROM orders_archive a, jsonb_populate_record(NULL::orders, a.data->'order') o
+            WHERE a.id = $1
+            "#
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch archived order: {}", e)))
+    }
+
+    async fn find_items(&self, order_id: Uuid) -> Result<Vec<OrderItem>> {
+        sqlx::query_as::<_, OrderItem>(
+            r#"
+            SELECT i.*
+            FROM orders_archive a, jsonb_populate_recordset(NULL::order_items, a.data->'order_items') i
+            WHERE a.id = $1
+            ORDER BY i.created_at
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch archived order items: {}", e)))
+    }
+
+    async fn find_receipts(&self, order_id: Uuid) -> Result<Vec<PaymentReceipt>> {
+        sqlx::query_as::<_, PaymentReceipt>(
+            r#"
+            SELECT p.id AS payment_id, p.order_id, p.gateway, p.gateway_payment_id, p.amount,
+                   p.currency, p.status, p.receipt_url, p.processed_at
+            FROM orders_archive a, jsonb_populate_recordset(NULL::payments, a.data->'payments') p
+            WHERE a.id = $1
+            ORDER BY p.created_at
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch archived payments: {}", e)))
+    }
+
+    async fn stats(&self) -> Result<OrderArchiveStats> {
+        sqlx::query_as::<_, OrderArchiveStats>(
+            r#"
+            SELECT COUNT(*) AS archived_orders,
+                   MIN(created_at) AS oldest_order_at,
+                   MAX(archived_at) AS last_archived_at
+            FROM orders_archive
+            "#
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order archive stats: {}", e)))
+    }
+}
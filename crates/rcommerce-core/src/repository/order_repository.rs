@@ -8,6 +8,7 @@ use uuid::Uuid;
 
 use crate::{
     Result, Error,
+    models::{after_cursor_sql, Cursor},
     order::{Order, OrderItem, OrderStatus, PaymentStatus, FulfillmentStatus},
 };
 
@@ -23,6 +24,9 @@ pub trait OrderRepository: Send + Sync + 'static {
     /// List orders with filtering
     async fn list_orders(&self, filter: &OrderFilter) -> Result<Vec<Order>>;
     
+    /// Up to `limit` filtered orders after a cursor, newest first
+    async fn list_orders_after(&self, filter: &OrderFilter, after: Option<&Cursor>, limit: i64) -> Result<Vec<Order>>;
+    
     /// Count orders by filter
     async fn count_orders(&self, filter: &OrderFilter) -> Result<i64>;
     
@@ -41,7 +45,7 @@ pub trait OrderRepository: Send + Sync + 'static {
     /// Update fulfillment status
     async fn update_fulfillment_status(&self, id: Uuid, status: FulfillmentStatus) -> Result<()>;
     
-    /// Delete an order
+    /// Soft-delete an order; it stays restorable until purged
     async fn delete_order(&self, id: Uuid) -> Result<bool>;
     
     /// Get order items
@@ -82,36 +86,10 @@ impl PostgresOrderRepository {
     pub fn new(db: sqlx::PgPool) -> Self {
         Self { db }
     }
-}
-
-#[async_trait]
-impl OrderRepository for PostgresOrderRepository {
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<Order>> {
-        let order = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders WHERE id = $1"
-        )
-        .bind(id)
-        .fetch_optional(&self.db)
-        .await
-        .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))?;
-        
-        Ok(order)
-    }
-    
-    async fn find_by_order_number(&self, order_number: &str) -> Result<Option<Order>> {
-        let order = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders WHERE order_number = $1"
-        )
-        .bind(order_number)
-        .fetch_optional(&self.db)
-        .await
-        .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))?;
-        
-        Ok(order)
-    }
     
-    async fn list_orders(&self, filter: &OrderFilter) -> Result<Vec<Order>> {
-        let mut sql = String::from("SELECT * FROM orders WHERE 1=1");
+    /// Filtered orders newest first, after a cursor and limited if given
+    async fn query_orders(&self, filter: &OrderFilter, after: Option<&Cursor>, limit: Option<i64>) -> Result<Vec<Order>> {
+        let mut sql = String::from("SELECT * FROM orders WHERE deleted_at IS NULL");
         let mut bind_idx = 0;
         
         if filter.customer_id.is_some() {
@@ -139,7 +117,16 @@ impl OrderRepository for PostgresOrderRepository {
             sql.push_str(&format!(" AND created_at <= ${}", bind_idx));
         }
         
-        sql.push_str(" ORDER BY created_at DESC");
+        if after.is_some() {
+            bind_idx += 1;
+            sql.push_str(&format!(" AND {}", after_cursor_sql("created_at", "id", bind_idx)));
+            bind_idx += 1;
+        }
+        
+        sql.push_str(" ORDER BY created_at DESC, id DESC");
+        if limit.is_some() {
+            sql.push_str(&format!(" LIMIT ${}", bind_idx + 1));
+        }
         
         let mut query = sqlx::query_as::<_, Order>(&sql);
         
@@ -161,6 +148,12 @@ impl OrderRepository for PostgresOrderRepository {
         if let Some(date_to) = filter.date_to {
             query = query.bind(date_to);
         }
+        if let Some(after) = after {
+            query = query.bind(after.created_at).bind(after.id);
+        }
+        if let Some(limit) = limit {
+            query = query.bind(limit);
+        }
         
         let orders = query
             .fetch_all(&self.db)
@@ -169,9 +162,44 @@ impl OrderRepository for PostgresOrderRepository {
         
         Ok(orders)
     }
+}
+
+#[async_trait]
+impl OrderRepository for PostgresOrderRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Order>> {
+        let order = sqlx::query_as::<_, Order>(
+            "SELECT * FROM orders WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))?;
+        
+        Ok(order)
+    }
+    
+    async fn find_by_order_number(&self, order_number: &str) -> Result<Option<Order>> {
+        let order = sqlx::query_as::<_, Order>(
+            "SELECT * FROM orders WHERE order_number = $1 AND deleted_at IS NULL"
+        )
+        .bind(order_number)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to fetch order: {}", e)))?;
+        
+        Ok(order)
+    }
+    
+    async fn list_orders(&self, filter: &OrderFilter) -> Result<Vec<Order>> {
+        self.query_orders(filter, None, None).await
+    }
+    
+    async fn list_orders_after(&self, filter: &OrderFilter, after: Option<&Cursor>, limit: i64) -> Result<Vec<Order>> {
+        self.query_orders(filter, after, Some(limit)).await
+    }
     
     async fn count_orders(&self, filter: &OrderFilter) -> Result<i64> {
-        let mut sql = String::from("SELECT COUNT(*) FROM orders WHERE 1=1");
+        let mut sql = String::from("SELECT COUNT(*) FROM orders WHERE deleted_at IS NULL");
         let mut bind_idx = 0;
         
         if filter.customer_id.is_some() {
@@ -324,7 +352,7 @@ impl OrderRepository for PostgresOrderRepository {
     }
     
     async fn delete_order(&self, id: Uuid) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM orders WHERE id = $1")
+        let result = sqlx::query("UPDATE orders SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .execute(&self.db)
             .await
@@ -409,7 +437,7 @@ impl OrderRepository for PostgresOrderRepository {
     
     async fn get_customer_orders(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<Order>> {
         let orders = sqlx::query_as::<_, Order>(
-            "SELECT * FROM orders WHERE customer_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3"
+            "SELECT * FROM orders WHERE customer_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC LIMIT $2 OFFSET $3"
         )
         .bind(customer_id)
         .bind(limit)
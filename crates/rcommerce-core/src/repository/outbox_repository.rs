@@ -0,0 +1,139 @@
+//! Event outbox repository
+//!
+//! Writing domain events in the transaction of the change they describe,
+//! claiming pending ones for the outbox relay, and purging processed ones.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{events::DomainEvent, Error, Result};
+
+/// Postgres channel notified when an event is written, so relays wake up on commit
+pub const OUTBOX_CHANNEL: &str = "event_outbox";
+
+/// An event waiting in the outbox
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OutboxEntry {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    /// Claims so far, including this one
+    pub attempts: i32,
+}
+
+/// Write an event to the outbox on the caller's transaction; it is published
+/// once the transaction commits. Returns the event id.
+pub async fn enqueue_event(conn: &mut PgConnection, event: &DomainEvent) -> Result<Uuid> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO event_outbox (event_type, payload) VALUES ($1, $2) RETURNING id"
+    )
+    .bind(event.name())
+    .bind(event.data())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to write {} event to the outbox: {}", event.name(), e)))?;
+
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(OUTBOX_CHANNEL)
+        .bind(id.to_string())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to notify the outbox relay: {}", e)))?;
+    Ok(id)
+}
+
+/// Repository trait for the event outbox
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    /// Claim up to `limit` pending events, oldest first, for `lease_secs`;
+    /// events claimed `max_attempts` times are left alone
+    async fn claim(&self, limit: i64, lease_secs: u64, max_attempts: i32) -> Result<Vec<OutboxEntry>>;
+
+    /// Mark published events processed
+    async fn mark_processed(&self, ids: &[Uuid]) -> Result<()>;
+
+    /// Release an event that could not be published, to be claimed again
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<()>;
+
+    /// Delete events processed before `before`; returns how many
+    async fn purge_processed(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of OutboxRepository
+pub struct PostgresOutboxRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresOutboxRepository {
+    /// Create a new PostgreSQL outbox repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for PostgresOutboxRepository {
+    async fn claim(&self, limit: i64, lease_secs: u64, max_attempts: i32) -> Result<Vec<OutboxEntry>> {
+        let mut entries = sqlx::query_as::<_, OutboxEntry>(
+            r#"
+            UPDATE event_outbox
+            SET claimed_until = NOW() + make_interval(secs => $2),
+                attempts = attempts + 1
+            WHERE id IN (
+                SELECT id FROM event_outbox
+                WHERE processed_at IS NULL
+                  AND attempts < $3
+                  AND (claimed_until IS NULL OR claimed_until < NOW())
+                ORDER BY created_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, event_type, payload, created_at, attempts
+            "#
+        )
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .bind(max_attempts)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to claim outbox events: {}", e)))?;
+        entries.sort_by_key(|entry| entry.created_at);
+        Ok(entries)
+    }
+
+    async fn mark_processed(&self, ids: &[Uuid]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            "UPDATE event_outbox SET processed_at = NOW(), claimed_until = NULL, last_error = NULL WHERE id = ANY($1)"
+        )
+        .bind(ids)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark outbox events processed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query("UPDATE event_outbox SET claimed_until = NULL, last_error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to release outbox event: {}", e)))?;
+        Ok(())
+    }
+
+    async fn purge_processed(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM event_outbox WHERE processed_at < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge the event outbox: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,274 @@
+//! Payment capture repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    events::{DomainEvent, PaymentUpdated},
+    models::{NewPaymentCapture, PaymentAuthorization, PaymentCapture, ShipmentCapture},
+    repository::enqueue_event,
+};
+
+/// Columns of `PaymentAuthorization`
+const AUTHORIZATION_COLUMNS: &str = "id, order_id, gateway, gateway_payment_id, currency::text AS currency, \
+     status::text AS status, COALESCE(authorized_amount, amount) AS authorized_amount, captured_amount, \
+     authorization_expires_at, voided_at, created_at";
+
+/// Repository trait for payment captures
+#[async_trait]
+pub trait PaymentCaptureRepository: Send + Sync {
+    /// A payment that was authorized before it was captured
+    async fn find_authorization(&self, payment_id: Uuid) -> Result<Option<PaymentAuthorization>>;
+
+    /// Open authorizations of an order, oldest first
+    async fn order_authorizations(&self, order_id: Uuid) -> Result<Vec<PaymentAuthorization>>;
+
+    /// Open authorizations lapsing by `now`
+    async fn expired_authorizations(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<PaymentAuthorization>>;
+
+    async fn captures(&self, payment_id: Uuid) -> Result<Vec<PaymentCapture>>;
+
+    /// Record a capture; the payment (and its order) is paid once fully or
+    /// finally captured. Fails if the authorization no longer covers it.
+    async fn record_capture(&self, payment_id: Uuid, capture: &NewPaymentCapture) -> Result<PaymentCapture>;
+
+    /// Record a void; what was captured stays paid. None if the payment
+    /// isn't an open authorization.
+    async fn record_void(&self, payment_id: Uuid) -> Result<Option<PaymentAuthorization>>;
+
+    async fn shipment_capture(&self, shipment_id: Uuid) -> Result<Option<ShipmentCapture>>;
+
+    /// Override `payment.capture.on_fulfillment` for an order; None follows it
+    async fn set_capture_on_fulfillment(&self, order_id: Uuid, enabled: Option<bool>) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of PaymentCaptureRepository
+pub struct PostgresPaymentCaptureRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPaymentCaptureRepository {
+    /// Create a new PostgreSQL payment capture repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// What a capture left of its payment
+#[derive(sqlx::FromRow)]
+struct CapturedPayment {
+    order_id: Uuid,
+    gateway: String,
+    currency: String,
+    status: String,
+    captured_amount: Decimal,
+}
+
+#[async_trait]
+impl PaymentCaptureRepository for PostgresPaymentCaptureRepository {
+    async fn find_authorization(&self, payment_id: Uuid) -> Result<Option<PaymentAuthorization>> {
+        sqlx::query_as::<_, PaymentAuthorization>(&format!(
+            "SELECT {} FROM payments WHERE id = $1 AND authorized_amount IS NOT NULL",
+            AUTHORIZATION_COLUMNS
+        ))
+        .bind(payment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get authorization: {}", e)))
+    }
+
+    async fn order_authorizations(&self, order_id: Uuid) -> Result<Vec<PaymentAuthorization>> {
+        sqlx::query_as::<_, PaymentAuthorization>(&format!(
+            "SELECT {} FROM payments WHERE order_id = $1 AND status = 'authorized' ORDER BY created_at",
+            AUTHORIZATION_COLUMNS
+        ))
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list authorizations: {}", e)))
+    }
+
+    async fn expired_authorizations(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<PaymentAuthorization>> {
+        sqlx::query_as::<_, PaymentAuthorization>(&format!(
+            r#"
+            SELECT {} FROM payments
+            WHERE status = 'authorized' AND authorization_expires_at <= $1
+            ORDER BY authorization_expires_at
+            LIMIT $2
+            "#,
+            AUTHORIZATION_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list expired authorizations: {}", e)))
+    }
+
+    async fn captures(&self, payment_id: Uuid) -> Result<Vec<PaymentCapture>> {
+        sqlx::query_as::<_, PaymentCapture>(
+            "SELECT * FROM payment_captures WHERE payment_id = $1 ORDER BY created_at",
+        )
+        .bind(payment_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list captures: {}", e)))
+    }
+
+    async fn record_capture(&self, payment_id: Uuid, capture: &NewPaymentCapture) -> Result<PaymentCapture> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let payment = sqlx::query_as::<_, CapturedPayment>(
+            r#"
+            UPDATE payments
+            SET captured_amount = captured_amount + $2,
+                status = CASE WHEN $3 OR captured_amount + $2 >= authorized_amount THEN 'paid' ELSE status END,
+                processed_at = COALESCE(processed_at, NOW()),
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'authorized' AND captured_amount + $2 <= authorized_amount
+            RETURNING order_id, gateway, currency::text AS currency, status::text AS status, captured_amount
+            "#
+        )
+        .bind(payment_id)
+        .bind(capture.amount)
+        .bind(capture.final_capture)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update payment: {}", e)))?
+        .ok_or_else(|| Error::validation("The payment is no longer authorized for this amount"))?;
+
+        let recorded = sqlx::query_as::<_, PaymentCapture>(
+            r#"
+            INSERT INTO payment_captures (payment_id, order_id, amount, currency, gateway_capture_id,
+                                          final_capture, source, shipment_id, captured_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(payment_id)
+        .bind(payment.order_id)
+        .bind(capture.amount)
+        .bind(&payment.currency)
+        .bind(&capture.gateway_capture_id)
+        .bind(capture.final_capture)
+        .bind(capture.source)
+        .bind(capture.shipment_id)
+        .bind(capture.captured_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record capture: {}", e)))?;
+
+        if payment.status == "paid" {
+            sqlx::query("UPDATE orders SET payment_status = 'paid', updated_at = NOW() WHERE id = $1")
+                .bind(payment.order_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to update order: {}", e)))?;
+            let event = DomainEvent::PaymentUpdated(PaymentUpdated {
+                payment_id: Some(payment_id),
+                order_id: payment.order_id,
+                status: "paid".to_string(),
+                previous_status: Some("authorized".to_string()),
+                amount: payment.captured_amount,
+                currency: payment.currency.clone(),
+                gateway: Some(payment.gateway.clone()),
+            });
+            enqueue_event(&mut tx, &event).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(recorded)
+    }
+
+    async fn record_void(&self, payment_id: Uuid) -> Result<Option<PaymentAuthorization>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let voided = sqlx::query_as::<_, PaymentAuthorization>(&format!(
+            r#"
+            UPDATE payments
+            SET status = (CASE WHEN captured_amount > 0 THEN 'paid' ELSE 'cancelled' END)::payment_status,
+                voided_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'authorized'
+            RETURNING {}
+            "#,
+            AUTHORIZATION_COLUMNS
+        ))
+        .bind(payment_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to void payment: {}", e)))?;
+
+        if let Some(payment) = &voided {
+            sqlx::query(
+                "UPDATE orders SET payment_status = $2::payment_status, updated_at = NOW() \
+                 WHERE id = $1 AND payment_status = 'authorized'",
+            )
+            .bind(payment.order_id)
+            .bind(&payment.status)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update order: {}", e)))?;
+            let event = DomainEvent::PaymentUpdated(PaymentUpdated {
+                payment_id: Some(payment.id),
+                order_id: payment.order_id,
+                status: payment.status.clone(),
+                previous_status: Some("authorized".to_string()),
+                amount: payment.captured_amount,
+                currency: payment.currency.clone(),
+                gateway: Some(payment.gateway.clone()),
+            });
+            enqueue_event(&mut tx, &event).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(voided)
+    }
+
+    async fn shipment_capture(&self, shipment_id: Uuid) -> Result<Option<ShipmentCapture>> {
+        sqlx::query_as::<_, ShipmentCapture>(
+            r#"
+            SELECT f.id AS shipment_id, f.order_id,
+                   COALESCE((SELECT SUM(fi.quantity * oi.price)
+                             FROM fulfillment_items fi
+                             JOIN order_items oi ON oi.id = fi.order_item_id
+                             WHERE fi.fulfillment_id = f.id), 0) AS value,
+                   COALESCE((SELECT SUM(oi.quantity) FROM order_items oi WHERE oi.order_id = f.order_id), 0)
+                   - COALESCE((SELECT SUM(fi.quantity)
+                               FROM fulfillment_items fi
+                               JOIN fulfillments sf ON sf.id = fi.fulfillment_id
+                               WHERE sf.order_id = f.order_id
+                                 AND (sf.id = f.id OR sf.status IN ('shipped', 'delivered', 'picked_up'))), 0)
+                     AS unshipped_units,
+                   o.capture_on_fulfillment,
+                   EXISTS (SELECT 1 FROM payment_captures pc WHERE pc.shipment_id = f.id) AS captured
+            FROM fulfillments f
+            JOIN orders o ON o.id = f.order_id
+            WHERE f.id = $1
+            "#
+        )
+        .bind(shipment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipment: {}", e)))
+    }
+
+    async fn set_capture_on_fulfillment(&self, order_id: Uuid, enabled: Option<bool>) -> Result<bool> {
+        let result = sqlx::query("UPDATE orders SET capture_on_fulfillment = $2, updated_at = NOW() WHERE id = $1")
+            .bind(order_id)
+            .bind(enabled)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update order: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,166 @@
+//! Payment session repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{NewPaymentSession, PaymentSession, PaymentSessionStatus, PaymentSessionUpdate},
+};
+
+/// Repository trait for payment sessions
+#[async_trait]
+pub trait PaymentSessionRepository: Send + Sync {
+    async fn create(&self, session: &NewPaymentSession) -> Result<PaymentSession>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<PaymentSession>>;
+
+    /// Latest session of a gateway payment
+    async fn find_by_payment(&self, gateway_payment_id: &str) -> Result<Option<PaymentSession>>;
+
+    /// Record what the gateway said; a succeeded payment is recorded
+    /// against the order with its receipt. None once the session is settled.
+    async fn update(&self, id: Uuid, update: &PaymentSessionUpdate) -> Result<Option<PaymentSession>>;
+}
+
+/// PostgreSQL implementation of PaymentSessionRepository
+pub struct PostgresPaymentSessionRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPaymentSessionRepository {
+    /// Create a new PostgreSQL payment session repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl PaymentSessionRepository for PostgresPaymentSessionRepository {
+    async fn create(&self, session: &NewPaymentSession) -> Result<PaymentSession> {
+        sqlx::query_as::<_, PaymentSession>(
+            r#"
+            INSERT INTO payment_sessions (order_id, gateway, amount, currency, return_url)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(session.order_id)
+        .bind(&session.gateway)
+        .bind(session.amount)
+        .bind(session.currency.to_uppercase())
+        .bind(&session.return_url)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create payment session: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<PaymentSession>> {
+        sqlx::query_as::<_, PaymentSession>("SELECT * FROM payment_sessions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get payment session: {}", e)))
+    }
+
+    async fn find_by_payment(&self, gateway_payment_id: &str) -> Result<Option<PaymentSession>> {
+        sqlx::query_as::<_, PaymentSession>(
+            "SELECT * FROM payment_sessions WHERE gateway_payment_id = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(gateway_payment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get payment session: {}", e)))
+    }
+
+    async fn update(&self, id: Uuid, update: &PaymentSessionUpdate) -> Result<Option<PaymentSession>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        // The action is replaced: a settled payment has none left
+        let session = sqlx::query_as::<_, PaymentSession>(
+            r#"
+            UPDATE payment_sessions
+            SET gateway_payment_id = COALESCE($2, gateway_payment_id),
+                status = $3,
+                action_type = $4,
+                action_data = $5,
+                error_code = $6,
+                error_message = $7,
+                receipt_url = COALESCE($8, receipt_url),
+                expires_at = COALESCE($9, expires_at),
+                completed_at = CASE WHEN $3 IN ('authorized', 'succeeded', 'failed', 'cancelled', 'expired') THEN NOW() END,
+                updated_at = NOW()
+            WHERE id = $1 AND status NOT IN ('authorized', 'succeeded', 'failed', 'cancelled', 'expired')
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&update.gateway_payment_id)
+        .bind(update.status)
+        .bind(&update.action_type)
+        .bind(&update.action_data)
+        .bind(&update.error_code)
+        .bind(&update.error_message)
+        .bind(&update.receipt_url)
+        .bind(update.expires_at)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update payment session: {}", e)))?;
+
+        if let Some(session) = session.as_ref().filter(|s| s.status == PaymentSessionStatus::Succeeded) {
+            sqlx::query(
+                r#"
+                INSERT INTO payments (order_id, amount, currency, status, gateway, gateway_payment_id, receipt_url, processed_at)
+                VALUES ($1, $2, $3::currency, 'paid', $4, $5, $6, NOW())
+                ON CONFLICT (gateway, gateway_payment_id) WHERE gateway_payment_id IS NOT NULL
+                DO UPDATE SET
+                    status = 'paid',
+                    receipt_url = COALESCE(EXCLUDED.receipt_url, payments.receipt_url),
+                    processed_at = COALESCE(payments.processed_at, EXCLUDED.processed_at)
+                "#
+            )
+            .bind(session.order_id)
+            .bind(session.amount)
+            .bind(&session.currency)
+            .bind(&session.gateway)
+            .bind(&session.gateway_payment_id)
+            .bind(&session.receipt_url)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record payment: {}", e)))?;
+        }
+
+        // Authorized payments are recorded with nothing captured yet
+        if let Some(session) = session.as_ref().filter(|s| s.status == PaymentSessionStatus::Authorized) {
+            sqlx::query(
+                r#"
+                INSERT INTO payments (order_id, amount, currency, status, gateway, gateway_payment_id,
+                                      authorized_amount, authorization_expires_at)
+                VALUES ($1, $2, $3::currency, 'authorized', $4, $5, $2, $6)
+                ON CONFLICT (gateway, gateway_payment_id) WHERE gateway_payment_id IS NOT NULL
+                DO NOTHING
+                "#
+            )
+            .bind(session.order_id)
+            .bind(session.amount)
+            .bind(&session.currency)
+            .bind(&session.gateway)
+            .bind(&session.gateway_payment_id)
+            .bind(update.authorization_expires_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record authorization: {}", e)))?;
+            sqlx::query("UPDATE orders SET payment_status = 'authorized', updated_at = NOW() WHERE id = $1 AND payment_status = 'pending'")
+                .bind(session.order_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to update order: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(session)
+    }
+}
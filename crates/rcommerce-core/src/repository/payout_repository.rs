@@ -0,0 +1,305 @@
+//! Payout repository
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        Payout, PayoutLine, PayoutTotals, ReconciledLine, ReconciliationCount, RecordedTransaction,
+        UnsettledPayment,
+    },
+    payment::agnostic::{GatewayPayout, SettlementKind},
+};
+
+/// Repository trait for imported payouts
+#[async_trait]
+pub trait PayoutRepository: Send + Sync {
+    /// The payment a gateway knows as `reference`, with what was captured
+    async fn find_payment(&self, gateway: &str, reference: &str) -> Result<Option<RecordedTransaction>>;
+
+    /// The refund a gateway knows as `reference`
+    async fn find_refund(&self, gateway: &str, reference: &str) -> Result<Option<RecordedTransaction>>;
+
+    /// Store a payout, replacing the lines of an earlier import
+    async fn save_payout(&self, gateway: &str, payout: &GatewayPayout, lines: &[ReconciledLine]) -> Result<Payout>;
+
+    async fn list_payouts(&self, gateway: Option<&str>, limit: i64, offset: i64) -> Result<Vec<Payout>>;
+
+    async fn find_payout(&self, id: Uuid) -> Result<Option<Payout>>;
+
+    async fn payout_lines(&self, payout_id: Uuid) -> Result<Vec<PayoutLine>>;
+
+    /// Totals per currency of payouts arriving in `[from, to)`
+    async fn totals(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>) -> Result<Vec<PayoutTotals>>;
+
+    /// Line counts per status of payouts arriving in `[from, to)`
+    async fn status_counts(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>) -> Result<Vec<ReconciliationCount>>;
+
+    /// Mismatched and unmatched lines of payouts arriving in `[from, to)`
+    async fn flagged_lines(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>, limit: i64) -> Result<Vec<PayoutLine>>;
+
+    /// Payments of `gateways` taken in `[from, to)` that no payout paid out
+    async fn unsettled_payments(&self, gateways: &[String], from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<Vec<UnsettledPayment>>;
+}
+
+/// PostgreSQL implementation of PayoutRepository
+pub struct PostgresPayoutRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPayoutRepository {
+    /// Create a new PostgreSQL payout repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// Payouts in a range of arrival dates, optionally of one gateway
+const PAYOUT_RANGE: &str = "COALESCE(po.arrival_date, po.gateway_created_at) >= $1 \
+     AND COALESCE(po.arrival_date, po.gateway_created_at) < $2 \
+     AND ($3::text IS NULL OR po.gateway = $3)";
+
+#[async_trait]
+impl PayoutRepository for PostgresPayoutRepository {
+    async fn find_payment(&self, gateway: &str, reference: &str) -> Result<Option<RecordedTransaction>> {
+        sqlx::query_as::<_, RecordedTransaction>(
+            r#"
+            SELECT id,
+                   CASE WHEN authorized_amount IS NULL THEN amount ELSE captured_amount END AS amount,
+                   currency::text AS currency
+            FROM payments
+            WHERE gateway = $1 AND gateway_payment_id = $2
+            "#
+        )
+        .bind(gateway)
+        .bind(reference)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to find payment: {}", e)))
+    }
+
+    async fn find_refund(&self, gateway: &str, reference: &str) -> Result<Option<RecordedTransaction>> {
+        sqlx::query_as::<_, RecordedTransaction>(
+            r#"
+            SELECT r.id, r.amount, r.currency::text AS currency
+            FROM refunds r
+            JOIN payments p ON p.id = r.payment_id
+            WHERE p.gateway = $1 AND r.gateway_refund_id = $2
+            "#
+        )
+        .bind(gateway)
+        .bind(reference)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to find refund: {}", e)))
+    }
+
+    async fn save_payout(&self, gateway: &str, payout: &GatewayPayout, lines: &[ReconciledLine]) -> Result<Payout> {
+        let gross: rust_decimal::Decimal = payout
+            .transactions
+            .iter()
+            .filter(|t| matches!(t.kind, SettlementKind::Charge | SettlementKind::Refund))
+            .map(|t| t.amount)
+            .sum();
+        let fees: rust_decimal::Decimal = payout.transactions.iter().map(|t| t.fee).sum::<rust_decimal::Decimal>()
+            - payout
+                .transactions
+                .iter()
+                .filter(|t| t.kind == SettlementKind::Fee)
+                .map(|t| t.amount)
+                .sum::<rust_decimal::Decimal>();
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let saved = sqlx::query_as::<_, Payout>(
+            r#"
+            INSERT INTO payouts (gateway, gateway_payout_id, amount, currency, status, gross, fees,
+                                 arrival_date, gateway_created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (gateway, gateway_payout_id) DO UPDATE
+            SET amount = EXCLUDED.amount, status = EXCLUDED.status, gross = EXCLUDED.gross,
+                fees = EXCLUDED.fees, arrival_date = EXCLUDED.arrival_date, imported_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(gateway)
+        .bind(&payout.payout_id)
+        .bind(payout.amount)
+        .bind(&payout.currency)
+        .bind(&payout.status)
+        .bind(gross)
+        .bind(fees)
+        .bind(payout.arrival_date)
+        .bind(payout.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save payout: {}", e)))?;
+
+        sqlx::query("DELETE FROM payout_lines WHERE payout_id = $1")
+            .bind(saved.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to replace payout lines: {}", e)))?;
+
+        for line in lines {
+            let transaction = &line.transaction;
+            let is_refund = transaction.kind == SettlementKind::Refund;
+            let recorded_id = line.recorded.as_ref().map(|r| r.id);
+            sqlx::query(
+                r#"
+                INSERT INTO payout_lines (payout_id, gateway_transaction_id, kind, reference, amount, fee, net,
+                                          currency, description, payment_id, refund_id, expected_amount,
+                                          expected_currency, status)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                "#
+            )
+            .bind(saved.id)
+            .bind(&transaction.transaction_id)
+            .bind(transaction.kind)
+            .bind(&transaction.reference)
+            .bind(transaction.amount)
+            .bind(transaction.fee)
+            .bind(transaction.net)
+            .bind(&transaction.currency)
+            .bind(&transaction.description)
+            .bind(recorded_id.filter(|_| !is_refund))
+            .bind(recorded_id.filter(|_| is_refund))
+            .bind(line.recorded.as_ref().map(|r| r.amount))
+            .bind(line.recorded.as_ref().map(|r| r.currency.clone()))
+            .bind(line.status)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to save payout line: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(saved)
+    }
+
+    async fn list_payouts(&self, gateway: Option<&str>, limit: i64, offset: i64) -> Result<Vec<Payout>> {
+        sqlx::query_as::<_, Payout>(
+            r#"
+            SELECT * FROM payouts
+            WHERE ($1::text IS NULL OR gateway = $1)
+            ORDER BY COALESCE(arrival_date, gateway_created_at) DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(gateway)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list payouts: {}", e)))
+    }
+
+    async fn find_payout(&self, id: Uuid) -> Result<Option<Payout>> {
+        sqlx::query_as::<_, Payout>("SELECT * FROM payouts WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get payout: {}", e)))
+    }
+
+    async fn payout_lines(&self, payout_id: Uuid) -> Result<Vec<PayoutLine>> {
+        sqlx::query_as::<_, PayoutLine>(
+            "SELECT * FROM payout_lines WHERE payout_id = $1 ORDER BY kind, gateway_transaction_id",
+        )
+        .bind(payout_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list payout lines: {}", e)))
+    }
+
+    async fn totals(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>) -> Result<Vec<PayoutTotals>> {
+        sqlx::query_as::<_, PayoutTotals>(&format!(
+            r#"
+            SELECT po.currency, COUNT(*) AS payouts,
+                   COALESCE(SUM(po.gross), 0) AS gross,
+                   COALESCE(SUM(po.fees), 0) AS fees,
+                   COALESCE(SUM(po.amount), 0) AS net
+            FROM payouts po
+            WHERE {}
+            GROUP BY po.currency
+            ORDER BY po.currency
+            "#,
+            PAYOUT_RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(gateway)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to total payouts: {}", e)))
+    }
+
+    async fn status_counts(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>) -> Result<Vec<ReconciliationCount>> {
+        sqlx::query_as::<_, ReconciliationCount>(&format!(
+            r#"
+            SELECT pl.status, COUNT(*) AS lines, COALESCE(SUM(pl.amount), 0) AS amount
+            FROM payout_lines pl
+            JOIN payouts po ON po.id = pl.payout_id
+            WHERE {}
+            GROUP BY pl.status
+            ORDER BY pl.status
+            "#,
+            PAYOUT_RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(gateway)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to count payout lines: {}", e)))
+    }
+
+    async fn flagged_lines(&self, from: DateTime<Utc>, to: DateTime<Utc>, gateway: Option<&str>, limit: i64) -> Result<Vec<PayoutLine>> {
+        sqlx::query_as::<_, PayoutLine>(&format!(
+            r#"
+            SELECT pl.*
+            FROM payout_lines pl
+            JOIN payouts po ON po.id = pl.payout_id
+            WHERE pl.status IN ('amount_mismatch', 'unmatched') AND {}
+            ORDER BY COALESCE(po.arrival_date, po.gateway_created_at), pl.gateway_transaction_id
+            LIMIT $4
+            "#,
+            PAYOUT_RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(gateway)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list flagged payout lines: {}", e)))
+    }
+
+    async fn unsettled_payments(&self, gateways: &[String], from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<Vec<UnsettledPayment>> {
+        sqlx::query_as::<_, UnsettledPayment>(
+            r#"
+            SELECT p.id, p.order_id, p.gateway, p.gateway_payment_id,
+                   CASE WHEN p.authorized_amount IS NULL THEN p.amount ELSE p.captured_amount END AS amount,
+                   p.currency::text AS currency, p.processed_at
+            FROM payments p
+            WHERE p.gateway = ANY($1)
+              AND p.status IN ('paid', 'refunded')
+              AND p.processed_at >= $2 AND p.processed_at < $3
+              AND NOT EXISTS (SELECT 1 FROM payout_lines pl WHERE pl.payment_id = p.id)
+            ORDER BY p.processed_at
+            LIMIT $4
+            "#
+        )
+        .bind(gateways)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list unsettled payments: {}", e)))
+    }
+}
@@ -0,0 +1,211 @@
+//! Pickup repository: pickup locations, their stock and pickup shipments
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    models::FulfillmentStatus,
+    order::LocationStock,
+    shipping::pickup::{PickupAvailability, PickupLocation, PickupShipment, UpdatePickupLocation},
+    Error, Result,
+};
+
+/// Repository trait for local pickup
+#[async_trait]
+pub trait PickupRepository: Send + Sync {
+    /// Active locations offering pickup
+    async fn locations(&self) -> Result<Vec<PickupLocation>>;
+
+    /// Change the pickup settings of an inventory location
+    async fn update_location(&self, location_id: Uuid, update: &UpdatePickupLocation) -> Result<Option<PickupLocation>>;
+
+    /// Stock of a product (any variant if `variant_id` is None) at each pickup location
+    async fn availability(&self, product_id: Uuid, variant_id: Option<Uuid>) -> Result<Vec<PickupAvailability>>;
+
+    /// Units of these products in stock at pickup locations
+    async fn stock(&self, product_ids: &[Uuid]) -> Result<Vec<LocationStock>>;
+
+    /// Record the location an order is collected from
+    async fn set_order_location(&self, order_id: Uuid, location_id: Uuid) -> Result<()>;
+
+    async fn find_shipment(&self, shipment_id: Uuid) -> Result<Option<PickupShipment>>;
+
+    /// Move a shipment to ready_for_pickup or picked_up, and its order with
+    /// it once all its live shipments are
+    async fn set_status(&self, shipment_id: Uuid, status: FulfillmentStatus) -> Result<PickupShipment>;
+}
+
+/// PostgreSQL implementation of PickupRepository
+pub struct PostgresPickupRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPickupRepository {
+    /// Create a new PostgreSQL pickup repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+const PICKUP_LOCATION_COLUMNS: &str = "id, name, code, address, pickup_instructions, pickup_hours";
+
+const PICKUP_SHIPMENT_SQL: &str = r#"
+    SELECT f.id, f.order_id, o.order_number, o.email,
+           COALESCE(c.email_notifications, true) AS email_notifications,
+           f.status, o.pickup_location_id AS location_id, l.name AS location_name,
+           l.pickup_instructions, l.pickup_hours, f.ready_for_pickup_at, f.picked_up_at
+    FROM fulfillments f
+    JOIN orders o ON o.id = f.order_id
+    LEFT JOIN customers c ON c.id = o.customer_id
+    LEFT JOIN inventory_locations l ON l.id = o.pickup_location_id
+"#;
+
+#[async_trait]
+impl PickupRepository for PostgresPickupRepository {
+    async fn locations(&self) -> Result<Vec<PickupLocation>> {
+        sqlx::query_as::<_, PickupLocation>(&format!(
+            "SELECT {} FROM inventory_locations WHERE is_active AND pickup_enabled ORDER BY name",
+            PICKUP_LOCATION_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list pickup locations: {}", e)))
+    }
+
+    async fn update_location(&self, location_id: Uuid, update: &UpdatePickupLocation) -> Result<Option<PickupLocation>> {
+        sqlx::query_as::<_, PickupLocation>(&format!(
+            r#"
+            UPDATE inventory_locations
+            SET pickup_enabled = COALESCE($2, pickup_enabled),
+                pickup_instructions = CASE WHEN $3::TEXT IS NULL THEN pickup_instructions ELSE NULLIF($3, '') END,
+                pickup_hours = CASE WHEN $4::TEXT IS NULL THEN pickup_hours ELSE NULLIF($4, '') END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            PICKUP_LOCATION_COLUMNS
+        ))
+        .bind(location_id)
+        .bind(update.pickup_enabled)
+        .bind(update.pickup_instructions.as_deref())
+        .bind(update.pickup_hours.as_deref())
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update pickup location: {}", e)))
+    }
+
+    async fn availability(&self, product_id: Uuid, variant_id: Option<Uuid>) -> Result<Vec<PickupAvailability>> {
+        sqlx::query_as::<_, PickupAvailability>(
+            r#"
+            SELECT l.id AS location_id, l.code, l.name,
+                   COALESCE(SUM(il.available_quantity), 0)::INT4 AS available
+            FROM inventory_locations l
+            LEFT JOIN inventory_levels il
+              ON il.location_id = l.id AND il.product_id = $1
+             AND ($2::UUID IS NULL OR il.variant_id = $2)
+            WHERE l.is_active AND l.pickup_enabled
+            GROUP BY l.id, l.code, l.name
+            ORDER BY l.name
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get pickup availability: {}", e)))
+    }
+
+    async fn stock(&self, product_ids: &[Uuid]) -> Result<Vec<LocationStock>> {
+        sqlx::query_as::<_, LocationStock>(
+            r#"
+            SELECT il.location_id, il.product_id, il.variant_id, il.available_quantity AS available
+            FROM inventory_levels il
+            JOIN inventory_locations l ON l.id = il.location_id
+            WHERE l.is_active AND l.pickup_enabled AND il.product_id = ANY($1) AND il.available_quantity > 0
+            "#
+        )
+        .bind(product_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get stock at pickup locations: {}", e)))
+    }
+
+    async fn set_order_location(&self, order_id: Uuid, location_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE orders SET pickup_location_id = $2, updated_at = NOW() WHERE id = $1")
+            .bind(order_id)
+            .bind(location_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to set order pickup location: {}", e)))?;
+        Ok(())
+    }
+
+    async fn find_shipment(&self, shipment_id: Uuid) -> Result<Option<PickupShipment>> {
+        sqlx::query_as::<_, PickupShipment>(&format!("{} WHERE f.id = $1", PICKUP_SHIPMENT_SQL))
+            .bind(shipment_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get shipment: {}", e)))
+    }
+
+    async fn set_status(&self, shipment_id: Uuid, status: FulfillmentStatus) -> Result<PickupShipment> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let order_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE fulfillments
+            SET status = $2,
+                ready_for_pickup_at = CASE WHEN $2 = 'ready_for_pickup' THEN NOW() ELSE ready_for_pickup_at END,
+                picked_up_at = CASE WHEN $2 = 'picked_up' THEN NOW() ELSE picked_up_at END,
+                delivered_at = CASE WHEN $2 = 'picked_up' THEN NOW() ELSE delivered_at END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING order_id
+            "#
+        )
+        .bind(shipment_id)
+        .bind(status)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update pickup status: {}", e)))?
+        .ok_or_else(|| Error::not_found("Shipment not found"))?;
+
+        // The order is picked up once all its live shipments are
+        sqlx::query(
+            r#"
+            UPDATE orders
+            SET fulfillment_status = shipments.status, updated_at = NOW()
+            FROM (
+                SELECT CASE
+                    WHEN bool_and(status = 'picked_up') THEN 'picked_up'
+                    WHEN bool_and(status IN ('ready_for_pickup', 'picked_up')) THEN 'ready_for_pickup'
+                    ELSE 'partial'
+                END::fulfillment_status AS status
+                FROM fulfillments
+                WHERE order_id = $1 AND status NOT IN ('cancelled', 'returned')
+                HAVING COUNT(*) > 0
+            ) shipments
+            WHERE orders.id = $1
+            "#
+        )
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update order fulfillment status: {}", e)))?;
+
+        let shipment = sqlx::query_as::<_, PickupShipment>(&format!("{} WHERE f.id = $1", PICKUP_SHIPMENT_SQL))
+            .bind(shipment_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get shipment: {}", e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to commit pickup status: {}", e)))?;
+        Ok(shipment)
+    }
+}
@@ -0,0 +1,487 @@
+//! Purchase order dispatch repository
+//!
+//! Suppliers' dispatch settings, the dispatch and acknowledgment state of
+//! placed purchase orders, their events and discrepancies. Recording an
+//! acknowledgment, discrepancies or alerts writes the matching events in
+//! the same transaction.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    inventory::po_dispatch::{
+        AcknowledgePurchaseOrderRequest, Discrepancy, NewDiscrepancy, PoDispatchMethod, PurchaseOrderEvent,
+        PurchaseOrderEventType, SaveDispatchSettingsRequest, SupplierDispatchSettings,
+    },
+    inventory::purchasing::{PurchaseOrder, PurchaseOrderItem, Supplier},
+    models::CustomerRole,
+};
+
+/// Placed orders (ordered or partially received)
+const PLACED: &str = "status IN ('ordered', 'partially_received')";
+
+/// Orders sent per run of the `po_dispatch` job
+const DISPATCH_BATCH: i64 = 100;
+
+/// Repository trait for purchase order dispatch
+#[async_trait]
+pub trait PoDispatchRepository: Send + Sync {
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>>;
+
+    async fn find_settings(&self, supplier_id: Uuid) -> Result<Option<SupplierDispatchSettings>>;
+
+    /// Create or replace a supplier's dispatch settings
+    async fn save_settings(&self, supplier_id: Uuid, request: &SaveDispatchSettingsRequest) -> Result<SupplierDispatchSettings>;
+
+    /// A purchase order with its dispatch attempts and last dispatch error
+    async fn find_purchase_order(&self, id: Uuid) -> Result<Option<(PurchaseOrder, i32, Option<String>)>>;
+
+    async fn purchase_order_items(&self, id: Uuid) -> Result<Vec<PurchaseOrderItem>>;
+
+    async fn find_by_ack_token(&self, token: &str) -> Result<Option<PurchaseOrder>>;
+
+    async fn ack_token(&self, id: Uuid) -> Result<Option<String>>;
+
+    /// Placed orders pending dispatch, or failed with attempts left, oldest first
+    async fn dispatchable(&self, max_attempts: i32) -> Result<Vec<Uuid>>;
+
+    async fn mark_dispatched(&self, id: Uuid, method: PoDispatchMethod) -> Result<()>;
+
+    async fn mark_dispatch_failed(&self, id: Uuid, error: &str) -> Result<()>;
+
+    /// Confirm the lines (omitted values as ordered), the supplier's
+    /// reference and ETA
+    async fn acknowledge(&self, id: Uuid, actor_id: Option<Uuid>, request: &AcknowledgePurchaseOrderRequest) -> Result<()>;
+
+    async fn add_discrepancies(&self, purchase_order_id: Uuid, discrepancies: &[NewDiscrepancy]) -> Result<Vec<Discrepancy>>;
+
+    /// Discrepancies, newest first
+    async fn list_discrepancies(&self, open_only: bool, supplier_id: Option<Uuid>, limit: i64) -> Result<Vec<Discrepancy>>;
+
+    /// Resolve an open discrepancy; None if there is no such open discrepancy
+    async fn resolve_discrepancy(&self, id: Uuid, actor_id: Uuid, resolution: &str) -> Result<Option<Discrepancy>>;
+
+    /// A purchase order's events, oldest first
+    async fn events(&self, purchase_order_id: Uuid) -> Result<Vec<PurchaseOrderEvent>>;
+
+    /// A purchase order's discrepancies, oldest first
+    async fn discrepancies(&self, purchase_order_id: Uuid) -> Result<Vec<Discrepancy>>;
+
+    /// Placed orders sent over `hours` ago, unacknowledged and not alerted about
+    async fn ack_overdue(&self, hours: i64) -> Result<Vec<PurchaseOrder>>;
+
+    /// Placed orders past their expected date and not alerted about
+    async fn late(&self) -> Result<Vec<PurchaseOrder>>;
+
+    /// Record that staff were alerted (`AckOverdue` or `Overdue`)
+    async fn mark_alerted(&self, ids: &[Uuid], event: PurchaseOrderEventType) -> Result<()>;
+
+    /// Staff with any of the roles, as (id, email)
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>>;
+}
+
+/// PostgreSQL purchase order dispatch repository
+#[derive(Clone)]
+pub struct PostgresPoDispatchRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPoDispatchRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+async fn insert_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    purchase_order_id: Uuid,
+    event: PurchaseOrderEventType,
+    details: serde_json::Value,
+    actor_id: Option<Uuid>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO purchase_order_events (purchase_order_id, event, details, actor_id) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(purchase_order_id)
+    .bind(event)
+    .bind(details)
+    .bind(actor_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to record purchase order event: {}", e)))?;
+    Ok(())
+}
+
+#[async_trait]
+impl PoDispatchRepository for PostgresPoDispatchRepository {
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>> {
+        sqlx::query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get supplier: {}", e)))
+    }
+
+    async fn find_settings(&self, supplier_id: Uuid) -> Result<Option<SupplierDispatchSettings>> {
+        sqlx::query_as::<_, SupplierDispatchSettings>("SELECT * FROM supplier_po_dispatch WHERE supplier_id = $1")
+            .bind(supplier_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get dispatch settings: {}", e)))
+    }
+
+    async fn save_settings(&self, supplier_id: Uuid, request: &SaveDispatchSettingsRequest) -> Result<SupplierDispatchSettings> {
+        sqlx::query_as::<_, SupplierDispatchSettings>(
+            r#"
+            INSERT INTO supplier_po_dispatch (supplier_id, method, email, sftp_target)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (supplier_id) DO UPDATE SET
+                method = EXCLUDED.method,
+                email = EXCLUDED.email,
+                sftp_target = EXCLUDED.sftp_target
+            RETURNING *
+            "#
+        )
+        .bind(supplier_id)
+        .bind(request.method)
+        .bind(&request.email)
+        .bind(&request.sftp_target)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save dispatch settings: {}", e)))
+    }
+
+    async fn find_purchase_order(&self, id: Uuid) -> Result<Option<(PurchaseOrder, i32, Option<String>)>> {
+        let Some(purchase_order) = sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))?
+        else {
+            return Ok(None);
+        };
+
+        let (attempts, error) = sqlx::query_as::<_, (i32, Option<String>)>(
+            "SELECT dispatch_attempts, dispatch_error FROM purchase_orders WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))?;
+        Ok(Some((purchase_order, attempts, error)))
+    }
+
+    async fn purchase_order_items(&self, id: Uuid) -> Result<Vec<PurchaseOrderItem>> {
+        sqlx::query_as::<_, PurchaseOrderItem>(
+            "SELECT * FROM purchase_order_items WHERE purchase_order_id = $1 ORDER BY created_at, id"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase order lines: {}", e)))
+    }
+
+    async fn find_by_ack_token(&self, token: &str) -> Result<Option<PurchaseOrder>> {
+        sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE ack_token = $1")
+            .bind(token)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))
+    }
+
+    async fn ack_token(&self, id: Uuid) -> Result<Option<String>> {
+        sqlx::query_scalar::<_, Option<String>>("SELECT ack_token FROM purchase_orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map(Option::flatten)
+            .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))
+    }
+
+    async fn dispatchable(&self, max_attempts: i32) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(&format!(
+            r#"
+            SELECT id FROM purchase_orders
+            WHERE {}
+              AND (dispatch_status = 'pending' OR (dispatch_status = 'failed' AND dispatch_attempts < $1))
+            ORDER BY ordered_at
+            LIMIT $2
+            "#,
+            PLACED
+        ))
+        .bind(max_attempts)
+        .bind(DISPATCH_BATCH)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list purchase orders to send: {}", e)))
+    }
+
+    async fn mark_dispatched(&self, id: Uuid, method: PoDispatchMethod) -> Result<()> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            UPDATE purchase_orders SET
+                dispatch_status = 'sent', dispatched_at = NOW(),
+                dispatch_attempts = dispatch_attempts + 1, dispatch_error = NULL
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update purchase order: {}", e)))?;
+        insert_event(&mut tx, id, PurchaseOrderEventType::Dispatched, serde_json::json!({ "method": method }), None).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))
+    }
+
+    async fn mark_dispatch_failed(&self, id: Uuid, error: &str) -> Result<()> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            UPDATE purchase_orders SET
+                dispatch_status = 'failed', dispatch_attempts = dispatch_attempts + 1, dispatch_error = $2
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update purchase order: {}", e)))?;
+        insert_event(&mut tx, id, PurchaseOrderEventType::DispatchFailed, serde_json::json!({ "error": error }), None).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))
+    }
+
+    async fn acknowledge(&self, id: Uuid, actor_id: Option<Uuid>, request: &AcknowledgePurchaseOrderRequest) -> Result<()> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            UPDATE purchase_order_items SET confirmed_quantity = quantity_ordered, confirmed_unit_cost = unit_cost
+            WHERE purchase_order_id = $1
+            "#
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to confirm purchase order lines: {}", e)))?;
+
+        for item in &request.items {
+            sqlx::query(
+                r#"
+                UPDATE purchase_order_items SET
+                    confirmed_quantity = COALESCE($3, quantity_ordered),
+                    confirmed_unit_cost = COALESCE($4, unit_cost)
+                WHERE id = $1 AND purchase_order_id = $2
+                "#
+            )
+            .bind(item.item_id)
+            .bind(id)
+            .bind(item.quantity)
+            .bind(item.unit_cost)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to confirm purchase order lines: {}", e)))?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE purchase_orders SET
+                acknowledged_at = NOW(),
+                supplier_reference = COALESCE($2, supplier_reference),
+                expected_at = COALESCE($3, expected_at)
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(&request.supplier_reference)
+        .bind(request.expected_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to acknowledge purchase order: {}", e)))?;
+
+        let details = serde_json::json!({
+            "supplier_reference": request.supplier_reference,
+            "expected_at": request.expected_at,
+            "lines_changed": request.items.len(),
+        });
+        insert_event(&mut tx, id, PurchaseOrderEventType::Acknowledged, details, actor_id).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))
+    }
+
+    async fn add_discrepancies(&self, purchase_order_id: Uuid, discrepancies: &[NewDiscrepancy]) -> Result<Vec<Discrepancy>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let mut recorded = Vec::with_capacity(discrepancies.len());
+        for discrepancy in discrepancies {
+            let row = sqlx::query_as::<_, Discrepancy>(
+                r#"
+                INSERT INTO purchase_order_discrepancies (purchase_order_id, item_id, source, kind, expected, actual)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+                "#
+            )
+            .bind(purchase_order_id)
+            .bind(discrepancy.item_id)
+            .bind(discrepancy.source)
+            .bind(discrepancy.kind)
+            .bind(discrepancy.expected)
+            .bind(discrepancy.actual)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record discrepancy: {}", e)))?;
+            recorded.push(row);
+        }
+        let ids: Vec<Uuid> = recorded.iter().map(|discrepancy| discrepancy.id).collect();
+        insert_event(
+            &mut tx,
+            purchase_order_id,
+            PurchaseOrderEventType::Discrepancy,
+            serde_json::json!({ "discrepancy_ids": ids }),
+            None,
+        )
+        .await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(recorded)
+    }
+
+    async fn list_discrepancies(&self, open_only: bool, supplier_id: Option<Uuid>, limit: i64) -> Result<Vec<Discrepancy>> {
+        sqlx::query_as::<_, Discrepancy>(
+            r#"
+            SELECT d.* FROM purchase_order_discrepancies d
+            JOIN purchase_orders po ON po.id = d.purchase_order_id
+            WHERE (NOT $1 OR d.resolved_at IS NULL)
+              AND ($2::uuid IS NULL OR po.supplier_id = $2)
+            ORDER BY d.created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(open_only)
+        .bind(supplier_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list discrepancies: {}", e)))
+    }
+
+    async fn resolve_discrepancy(&self, id: Uuid, actor_id: Uuid, resolution: &str) -> Result<Option<Discrepancy>> {
+        sqlx::query_as::<_, Discrepancy>(
+            r#"
+            UPDATE purchase_order_discrepancies SET resolved_at = NOW(), resolved_by = $2, resolution = $3
+            WHERE id = $1 AND resolved_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(actor_id)
+        .bind(resolution)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve discrepancy: {}", e)))
+    }
+
+    async fn events(&self, purchase_order_id: Uuid) -> Result<Vec<PurchaseOrderEvent>> {
+        sqlx::query_as::<_, PurchaseOrderEvent>(
+            "SELECT * FROM purchase_order_events WHERE purchase_order_id = $1 ORDER BY created_at, id"
+        )
+        .bind(purchase_order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase order events: {}", e)))
+    }
+
+    async fn discrepancies(&self, purchase_order_id: Uuid) -> Result<Vec<Discrepancy>> {
+        sqlx::query_as::<_, Discrepancy>(
+            "SELECT * FROM purchase_order_discrepancies WHERE purchase_order_id = $1 ORDER BY created_at, id"
+        )
+        .bind(purchase_order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get discrepancies: {}", e)))
+    }
+
+    async fn ack_overdue(&self, hours: i64) -> Result<Vec<PurchaseOrder>> {
+        sqlx::query_as::<_, PurchaseOrder>(&format!(
+            r#"
+            SELECT * FROM purchase_orders
+            WHERE {}
+              AND dispatch_status = 'sent' AND acknowledged_at IS NULL AND ack_alerted_at IS NULL
+              AND dispatched_at < NOW() - make_interval(hours => $1::int)
+            ORDER BY dispatched_at
+            "#,
+            PLACED
+        ))
+        .bind(hours)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list unacknowledged purchase orders: {}", e)))
+    }
+
+    async fn late(&self) -> Result<Vec<PurchaseOrder>> {
+        sqlx::query_as::<_, PurchaseOrder>(&format!(
+            r#"
+            SELECT * FROM purchase_orders
+            WHERE {} AND expected_at < NOW() AND overdue_alerted_at IS NULL
+            ORDER BY expected_at
+            "#,
+            PLACED
+        ))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list late purchase orders: {}", e)))
+    }
+
+    async fn mark_alerted(&self, ids: &[Uuid], event: PurchaseOrderEventType) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let column = match event {
+            PurchaseOrderEventType::AckOverdue => "ack_alerted_at",
+            PurchaseOrderEventType::Overdue => "overdue_alerted_at",
+            _ => return Err(Error::Other(format!("{:?} is not an alert", event))),
+        };
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        sqlx::query(&format!("UPDATE purchase_orders SET {} = NOW() WHERE id = ANY($1)", column))
+            .bind(ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update purchase orders: {}", e)))?;
+        for id in ids {
+            insert_event(&mut tx, *id, event, serde_json::json!({}), None).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))
+    }
+
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>> {
+        let roles: Vec<String> = roles
+            .iter()
+            .map(|role| format!("{:?}", role).to_lowercase())
+            .collect();
+
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, email FROM customers WHERE role::text = ANY($1) ORDER BY email")
+            .bind(&roles)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list staff: {}", e)))
+    }
+}
@@ -3,7 +3,7 @@ use uuid::Uuid;
 
 use crate::{
     Result,
-    models::{Customer, CreateCustomerRequest, UpdateCustomerRequest},
+    models::{Customer, CreateCustomerRequest, UpdateCustomerRequest, Cursor, after_cursor_sql},
 };
 use crate::repository::traits::CustomerRepositoryTrait;
 use super::PostgresDb;
@@ -13,6 +13,15 @@ pub struct PostgresCustomerRepository {
     db: PostgresDb,
 }
 
+/// Deleted customers keep their email until purged, so the unique index
+/// can reject an email the lookups no longer find
+fn email_taken(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => crate::Error::validation("Email already exists"),
+        e => e.into(),
+    }
+}
+
 impl PostgresCustomerRepository {
     pub fn new(db: PostgresDb) -> Self {
         Self { db }
@@ -21,7 +30,7 @@ impl PostgresCustomerRepository {
     // Inherent methods for direct access
     pub async fn find_by_email(&self, email: &str) -> Result<Option<Customer>> {
         let customer = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM customers WHERE email = $1"
+            "SELECT * FROM customers WHERE email = $1 AND deleted_at IS NULL"
         )
         .bind(email)
         .fetch_optional(self.db.pool())
@@ -45,7 +54,8 @@ impl PostgresCustomerRepository {
         .bind(request.accepts_marketing)
         .bind(request.currency)
         .fetch_one(self.db.pool())
-        .await?;
+        .await
+        .map_err(email_taken)?;
         
         Ok(customer)
     }
@@ -66,7 +76,8 @@ impl PostgresCustomerRepository {
         .bind(request.currency)
         .bind(password_hash)
         .fetch_one(self.db.pool())
-        .await?;
+        .await
+        .map_err(email_taken)?;
         
         Ok(customer)
     }
@@ -135,7 +146,7 @@ impl PostgresCustomerRepository {
 impl CustomerRepositoryTrait for PostgresCustomerRepository {
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Customer>> {
         let customer = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM customers WHERE id = $1"
+            "SELECT * FROM customers WHERE id = $1 AND deleted_at IS NULL"
         )
         .bind(id)
         .fetch_optional(self.db.pool())
@@ -146,7 +157,7 @@ impl CustomerRepositoryTrait for PostgresCustomerRepository {
     
     async fn find_by_email(&self, email: &str) -> Result<Option<Customer>> {
         let customer = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM customers WHERE email = $1"
+            "SELECT * FROM customers WHERE email = $1 AND deleted_at IS NULL"
         )
         .bind(email)
         .fetch_optional(self.db.pool())
@@ -157,7 +168,7 @@ impl CustomerRepositoryTrait for PostgresCustomerRepository {
     
     async fn list(&self) -> Result<Vec<Customer>> {
         let customers = sqlx::query_as::<_, Customer>(
-            "SELECT * FROM customers ORDER BY created_at DESC"
+            "SELECT * FROM customers WHERE deleted_at IS NULL ORDER BY created_at DESC"
         )
         .fetch_all(self.db.pool())
         .await?;
@@ -165,6 +176,21 @@ impl CustomerRepositoryTrait for PostgresCustomerRepository {
         Ok(customers)
     }
     
+    async fn list_after(&self, after: Option<&Cursor>, limit: i64) -> Result<Vec<Customer>> {
+        let customers = sqlx::query_as::<_, Customer>(&format!(
+            "SELECT * FROM customers WHERE deleted_at IS NULL AND ($1::TIMESTAMPTZ IS NULL OR {}) \
+             ORDER BY created_at DESC, id DESC LIMIT $3",
+            after_cursor_sql("created_at", "id", 1)
+        ))
+        .bind(after.map(|c| c.created_at))
+        .bind(after.map(|c| c.id))
+        .bind(limit)
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        Ok(customers)
+    }
+    
     async fn create(&self, request: CreateCustomerRequest) -> Result<Customer> {
         let customer = sqlx::query_as::<_, Customer>(
             r#"
@@ -180,7 +206,8 @@ impl CustomerRepositoryTrait for PostgresCustomerRepository {
         .bind(request.accepts_marketing)
         .bind(request.currency)
         .fetch_one(self.db.pool())
-        .await?;
+        .await
+        .map_err(email_taken)?;
         
         Ok(customer)
     }
@@ -240,7 +267,7 @@ impl CustomerRepositoryTrait for PostgresCustomerRepository {
     }
     
     async fn delete(&self, id: Uuid) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM customers WHERE id = $1")
+        let result = sqlx::query("UPDATE customers SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .execute(self.db.pool())
             .await?;
@@ -2,9 +2,14 @@
 
 pub mod product_repository;
 pub mod customer_repository;
+pub mod replicas;
 
 pub use product_repository::PostgresProductRepository;
 pub use customer_repository::PostgresCustomerRepository;
+pub use replicas::{create_replica_set, ReadReplica, ReplicaSet, ReplicaStatus};
+
+use std::future::Future;
+use std::sync::Arc;
 
 use sqlx::{Pool, Postgres};
 
@@ -12,16 +17,48 @@ use sqlx::{Pool, Postgres};
 #[derive(Clone)]
 pub struct PostgresDb {
     pool: Pool<Postgres>,
+    replicas: Arc<ReplicaSet>,
 }
 
 impl PostgresDb {
     pub fn new(pool: Pool<Postgres>) -> Self {
-        Self { pool }
+        Self { pool, replicas: Arc::new(ReplicaSet::default()) }
+    }
+    
+    /// Route `read` queries to these replicas
+    pub fn with_read_replicas(mut self, replicas: ReplicaSet) -> Self {
+        self.replicas = Arc::new(replicas);
+        self
     }
     
+    /// The primary
     pub fn pool(&self) -> &Pool<Postgres> {
         &self.pool
     }
+    
+    pub fn replica_status(&self) -> Vec<ReplicaStatus> {
+        self.replicas.status()
+    }
+    
+    /// Run a read-only query on a replica, or the primary if there are none
+    /// or the replica can't be reached. Only for queries that tolerate
+    /// replication lag: never read back what was just written.
+    pub async fn read<T, F, Fut>(&self, query: F) -> std::result::Result<T, sqlx::Error>
+    where
+        F: Fn(Pool<Postgres>) -> Fut,
+        Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+    {
+        if let Some(replica) = self.replicas.pick() {
+            match query(replica.pool().clone()).await {
+                Err(e) if replicas::is_connection_error(&e) => {
+                    tracing::warn!("Read replica {} unavailable, using the primary: {}", replica.name(), e);
+                    self.replicas.mark_down(replica);
+                }
+                result => return result,
+            }
+        }
+        query(self.pool.clone()).await
+    }
 }
 
 /// Create PostgreSQL connection pool
@@ -6,7 +6,10 @@ use crate::{
     Result, Pagination, SortParams, SortDirection,
     models::{
         Product, ProductVariant, ProductImage, ProductFilter,
-        CreateProductRequest, UpdateProductRequest
+        CreateProductRequest, UpdateProductRequest,
+        CreateVariantRequest, Currency, ProductOptionSet, VariantOption,
+        Collection, CollectionType, collection_order_by, collection_rules_sql,
+        Cursor, after_cursor_sql, AttributeFacetCount, AttributeMatch,
     },
 };
 use crate::repository::traits::ProductRepositoryTrait;
@@ -25,7 +28,7 @@ impl PostgresProductRepository {
     // Inherent methods for direct access
     pub async fn find_by_slug(&self, slug: &str) -> Result<Option<Product>> {
         let product = sqlx::query_as::<_, Product>(
-            "SELECT * FROM products WHERE slug = $1"
+            "SELECT * FROM products WHERE slug = $1 AND deleted_at IS NULL"
         )
         .bind(slug)
         .fetch_optional(self.db.pool())
@@ -41,9 +44,9 @@ impl PostgresProductRepository {
                 title, slug, description, sku, price, compare_at_price, cost_price,
                 currency, inventory_quantity, inventory_policy, inventory_management,
                 continues_selling_when_out_of_stock, weight, weight_unit, requires_shipping,
-                is_active, is_featured, seo_title, seo_description
+                is_active, is_featured, seo_title, seo_description, vendor
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
             RETURNING *
             "#
         )
@@ -66,8 +69,16 @@ impl PostgresProductRepository {
         .bind(request.is_featured)
         .bind(request.seo_title)
         .bind(request.seo_description)
+        .bind(request.vendor)
         .fetch_one(self.db.pool())
-        .await?;
+        .await
+        .map_err(|e| match e {
+            // Deleted products keep their slug until purged
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                crate::Error::validation("Product slug already exists")
+            }
+            e => e.into(),
+        })?;
         
         Ok(product)
     }
@@ -82,6 +93,7 @@ impl PostgresProductRepository {
         let has_description = request.description.is_some();
         let has_price = request.price.is_some();
         let has_is_active = request.is_active.is_some();
+        let has_vendor = request.vendor.is_some();
         
         if has_title {
             param_count += 1;
@@ -103,6 +115,10 @@ impl PostgresProductRepository {
             param_count += 1;
             sets.push(format!("is_active = ${}", param_count));
         }
+        if has_vendor {
+            param_count += 1;
+            sets.push(format!("vendor = ${}", param_count));
+        }
         
         if sets.is_empty() {
             return Err(crate::Error::Validation("No fields to update".to_string()));
@@ -133,6 +149,9 @@ impl PostgresProductRepository {
         if let Some(is_active) = request.is_active {
             query_builder = query_builder.bind(is_active);
         }
+        if let Some(vendor) = request.vendor {
+            query_builder = query_builder.bind(vendor);
+        }
         query_builder = query_builder.bind(id);
         
         let product = query_builder
@@ -163,74 +182,435 @@ impl PostgresProductRepository {
         
         Ok(images)
     }
-}
-
-#[async_trait]
-impl ProductRepositoryTrait for PostgresProductRepository {
-    async fn find_by_id(&self, id: Uuid) -> Result<Option<Product>> {
-        let product = sqlx::query_as::<_, Product>(
-            "SELECT * FROM products WHERE id = $1"
+    
+    pub async fn find_variant(&self, product_id: Uuid, variant_id: Uuid) -> Result<Option<ProductVariant>> {
+        let variant = sqlx::query_as::<_, ProductVariant>(
+            "SELECT * FROM product_variants WHERE id = $1 AND product_id = $2"
         )
-        .bind(id)
+        .bind(variant_id)
+        .bind(product_id)
         .fetch_optional(self.db.pool())
         .await?;
         
-        Ok(product)
+        Ok(variant)
     }
     
-    async fn list(&self) -> Result<Vec<Product>> {
-        let products = sqlx::query_as::<_, Product>(
-            "SELECT * FROM products ORDER BY created_at DESC"
+    /// Option values of all of a product's variants, in option position order
+    pub async fn find_variant_options(&self, product_id: Uuid) -> Result<Vec<(Uuid, VariantOption)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT v.variant_id, o.name, v.value
+            FROM product_option_values v
+            JOIN product_options o ON o.id = v.option_id
+            WHERE o.product_id = $1
+            ORDER BY o.position, o.created_at
+            "#
         )
+        .bind(product_id)
         .fetch_all(self.db.pool())
         .await?;
         
-        Ok(products)
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("variant_id"), VariantOption { name: row.get("name"), value: row.get("value") }))
+            .collect())
     }
     
-    async fn find_with_filter(
+    /// Option names with the values in use, in the order values were first used
+    pub async fn find_options(&self, product_id: Uuid) -> Result<Vec<ProductOptionSet>> {
+        let options = sqlx::query_as::<_, ProductOptionSet>(
+            r#"
+            SELECT o.id, o.name, o.position,
+                ARRAY(
+                    SELECT v.value FROM product_option_values v
+                    WHERE v.option_id = o.id
+                    GROUP BY v.value
+                    ORDER BY MIN(v.created_at)
+                ) AS values
+            FROM product_options o
+            WHERE o.product_id = $1
+            ORDER BY o.position, o.created_at
+            "#
+        )
+        .bind(product_id)
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        Ok(options)
+    }
+    
+    pub async fn create_variant(
         &self,
-        filter: &ProductFilter,
-        pagination: &Pagination,
-        sort: Option<&SortParams>,
-    ) -> Result<Vec<Product>> {
-        let mut query = String::from("SELECT * FROM products WHERE 1=1");
-        let mut param_count = 0;
+        product_id: Uuid,
+        title: &str,
+        currency: Currency,
+        request: &CreateVariantRequest,
+    ) -> Result<ProductVariant> {
+        let mut tx = self.db.pool().begin().await?;
         
-        // Track which parameters we need to bind
-        let has_category = filter.category_id.is_some();
-        let has_price_min = filter.price_min.is_some();
-        let has_price_max = filter.price_max.is_some();
+        let variant = sqlx::query_as::<_, ProductVariant>(
+            r#"
+            INSERT INTO product_variants (
+                product_id, title, sku, barcode, price, compare_at_price, cost_price, currency,
+                inventory_quantity, inventory_policy, weight, weight_unit, requires_shipping, is_active
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING *
+            "#
+        )
+        .bind(product_id)
+        .bind(title)
+        .bind(&request.sku)
+        .bind(&request.barcode)
+        .bind(request.price)
+        .bind(request.compare_at_price)
+        .bind(request.cost_price)
+        .bind(currency)
+        .bind(request.inventory_quantity)
+        .bind(request.inventory_policy)
+        .bind(request.weight)
+        .bind(request.weight_unit)
+        .bind(request.requires_shipping)
+        .bind(request.is_active)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        Self::set_variant_options(&mut tx, product_id, variant.id, &request.options).await?;
+        tx.commit().await?;
+        
+        Ok(variant)
+    }
+    
+    /// Save a variant whose fields were updated in memory, replacing its
+    /// option values when `options` is given
+    pub async fn update_variant(&self, variant: &ProductVariant, options: Option<&[VariantOption]>) -> Result<ProductVariant> {
+        let mut tx = self.db.pool().begin().await?;
+        
+        let updated = sqlx::query_as::<_, ProductVariant>(
+            r#"
+            UPDATE product_variants SET
+                title = $2, sku = $3, barcode = $4, price = $5, compare_at_price = $6, cost_price = $7,
+                inventory_quantity = $8, inventory_policy = $9, weight = $10, weight_unit = $11,
+                requires_shipping = $12, is_active = $13, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(variant.id)
+        .bind(&variant.title)
+        .bind(&variant.sku)
+        .bind(&variant.barcode)
+        .bind(variant.price)
+        .bind(variant.compare_at_price)
+        .bind(variant.cost_price)
+        .bind(variant.inventory_quantity)
+        .bind(variant.inventory_policy)
+        .bind(variant.weight)
+        .bind(variant.weight_unit)
+        .bind(variant.requires_shipping)
+        .bind(variant.is_active)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        if let Some(options) = options {
+            Self::set_variant_options(&mut tx, variant.product_id, variant.id, options).await?;
+            Self::prune_options(&mut tx, variant.product_id).await?;
+        }
+        tx.commit().await?;
+        
+        Ok(updated)
+    }
+    
+    pub async fn delete_variant(&self, product_id: Uuid, variant_id: Uuid) -> Result<bool> {
+        let mut tx = self.db.pool().begin().await?;
+        
+        let result = sqlx::query("DELETE FROM product_variants WHERE id = $1 AND product_id = $2")
+            .bind(variant_id)
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::prune_options(&mut tx, product_id).await?;
+        tx.commit().await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Replace a variant's option values, adding any new option names to the product
+    async fn set_variant_options(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        product_id: Uuid,
+        variant_id: Uuid,
+        options: &[VariantOption],
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM product_option_values WHERE variant_id = $1")
+            .bind(variant_id)
+            .execute(&mut **tx)
+            .await?;
+        
+        for option in options {
+            let option_id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO product_options (product_id, name, position)
+                VALUES ($1, $2, (SELECT COALESCE(MAX(position) + 1, 0) FROM product_options WHERE product_id = $1))
+                ON CONFLICT (product_id, (lower(name))) DO UPDATE SET updated_at = NOW()
+                RETURNING id
+                "#
+            )
+            .bind(product_id)
+            .bind(&option.name)
+            .fetch_one(&mut **tx)
+            .await?;
+            
+            sqlx::query("INSERT INTO product_option_values (option_id, variant_id, value) VALUES ($1, $2, $3)")
+                .bind(option_id)
+                .bind(variant_id)
+                .bind(&option.value)
+                .execute(&mut **tx)
+                .await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Drop options no variant uses any more
+    async fn prune_options(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, product_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM product_options o
+            WHERE o.product_id = $1
+              AND NOT EXISTS (SELECT 1 FROM product_option_values v WHERE v.option_id = o.id)
+            "#
+        )
+        .bind(product_id)
+        .execute(&mut **tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// The conditions of a product filter, to follow `WHERE deleted_at IS NULL`
+    async fn filter_clause(&self, filter: &ProductFilter) -> Result<FilterClause> {
+        let mut clause = FilterClause::default();
         
         if let Some(status) = filter.status {
             match status {
                 crate::models::ProductStatus::Active => {
-                    query.push_str(" AND is_active = true");
+                    clause.conditions.push_str(" AND is_active = true");
                 }
                 crate::models::ProductStatus::Draft => {
-                    query.push_str(" AND is_active = false AND published_at IS NULL");
+                    clause.conditions.push_str(" AND is_active = false AND published_at IS NULL");
                 }
                 crate::models::ProductStatus::Archived => {}
             }
         }
         
-        if has_category {
-            param_count += 1;
-            query.push_str(&format!(
-                " AND id IN (SELECT product_id FROM product_category_relations WHERE category_id = ${})",
-                param_count
+        // Products in the category or any of its subcategories
+        if let Some(category_id) = filter.category_id {
+            let param = clause.bind(FilterValue::Uuid(category_id));
+            clause.conditions.push_str(&format!(
+                " AND id IN (SELECT product_id FROM product_category_relations WHERE category_id IN (\
+                 WITH RECURSIVE subtree AS (\
+                 SELECT id FROM product_categories WHERE id = ${} \
+                 UNION SELECT c.id FROM product_categories c JOIN subtree s ON c.parent_id = s.id\
+                 ) SELECT id FROM subtree))",
+                param
             ));
         }
         
-        if has_price_min {
-            param_count += 1;
-            query.push_str(&format!(" AND price >= ${}", param_count));
+        if let Some(collection_id) = filter.collection_id {
+            let collection = sqlx::query_as::<_, Collection>("SELECT * FROM collections WHERE id = $1")
+                .bind(collection_id)
+                .fetch_optional(self.db.pool())
+                .await?;
+            match collection {
+                None => clause.conditions.push_str(" AND FALSE"),
+                Some(collection) if collection.collection_type == CollectionType::Smart => {
+                    let (rules, values) =
+                        collection_rules_sql(&collection.rules, collection.disjunctive, clause.values.len() + 1);
+                    clause.conditions.push_str(&format!(" AND {}", rules));
+                    clause.values.extend(values.into_iter().map(FilterValue::Text));
+                    // Smart collections never sort by position
+                    clause.order_by = Some(collection_order_by(&collection, 0));
+                }
+                Some(collection) => {
+                    let param = clause.bind(FilterValue::Uuid(collection.id));
+                    clause.conditions.push_str(&format!(
+                        " AND id IN (SELECT product_id FROM collection_products WHERE collection_id = ${})",
+                        param
+                    ));
+                    clause.order_by = Some(collection_order_by(&collection, param));
+                }
+            }
         }
         
-        if has_price_max {
-            param_count += 1;
-            query.push_str(&format!(" AND price <= ${}", param_count));
+        if let Some(vendor) = filter.vendor.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            let param = clause.bind(FilterValue::Text(vendor.to_string()));
+            clause.conditions.push_str(&format!(" AND LOWER(vendor) = LOWER(${})", param));
+        }
+        
+        // Products with any of the tags
+        if !filter.tags.is_empty() {
+            let tags = filter.tags.iter().map(|tag| tag.trim().to_lowercase()).collect();
+            let param = clause.bind(FilterValue::TextArray(tags));
+            clause.conditions.push_str(&format!(
+                " AND id IN (SELECT ptr.product_id FROM product_tag_relations ptr \
+                 JOIN product_tags pt ON pt.id = ptr.tag_id WHERE LOWER(pt.name) = ANY(${}))",
+                param
+            ));
+        }
+        
+        if let Some(price_min) = filter.price_min {
+            let param = clause.bind(FilterValue::Decimal(price_min));
+            clause.conditions.push_str(&format!(" AND price >= ${}", param));
+        }
+        
+        if let Some(price_max) = filter.price_max {
+            let param = clause.bind(FilterValue::Decimal(price_max));
+            clause.conditions.push_str(&format!(" AND price <= ${}", param));
+        }
+        
+        // Products with a matching value of their own or of a variant
+        for condition in &filter.attributes {
+            let attribute = clause.bind(FilterValue::Uuid(condition.attribute_id));
+            let matches = match &condition.matches {
+                AttributeMatch::Options(options) => {
+                    format!("option_id = ANY(${})", clause.bind(FilterValue::UuidArray(options.clone())))
+                }
+                AttributeMatch::Boolean(value) => {
+                    format!("boolean_value = ${}", clause.bind(FilterValue::Bool(*value)))
+                }
+                AttributeMatch::Range { min, max } => {
+                    let mut range = "number_value IS NOT NULL".to_string();
+                    if let Some(min) = min {
+                        range.push_str(&format!(" AND number_value >= ${}", clause.bind(FilterValue::Decimal(*min))));
+                    }
+                    if let Some(max) = max {
+                        range.push_str(&format!(" AND number_value <= ${}", clause.bind(FilterValue::Decimal(*max))));
+                    }
+                    range
+                }
+            };
+            clause.conditions.push_str(&format!(
+                " AND id IN (SELECT product_id FROM product_attribute_values WHERE attribute_id = ${} AND {})",
+                attribute, matches
+            ));
+        }
+        
+        Ok(clause)
+    }
+    
+    /// Products of a filtered listing per value of some attributes, for
+    /// facet counts; see `AttributeFacetCount`
+    pub async fn attribute_facet_counts(
+        &self,
+        filter: &ProductFilter,
+        attribute_ids: &[Uuid],
+    ) -> Result<Vec<AttributeFacetCount>> {
+        if attribute_ids.is_empty() {
+            return Ok(Vec::new());
         }
+        let mut clause = self.filter_clause(filter).await?;
+        let attributes = clause.bind(FilterValue::UuidArray(attribute_ids.to_vec()));
+        // Number values are grouped per attribute, options and booleans
+        // per value
+        let query = format!(
+            r#"
+            SELECT v.attribute_id, v.option_id, v.boolean_value,
+                   COUNT(DISTINCT v.product_id) AS products,
+                   MIN(v.number_value) AS min_number, MAX(v.number_value) AS max_number
+            FROM product_attribute_values v
+            WHERE v.attribute_id = ANY(${})
+              AND v.product_id IN (SELECT id FROM products WHERE deleted_at IS NULL{})
+            GROUP BY v.attribute_id, v.option_id, v.boolean_value
+            "#,
+            attributes, clause.conditions
+        );
+        
+        let (query, values) = (query.as_str(), clause.values.as_slice());
+        let counts = self.db.read(move |pool| {
+            let mut query_builder = sqlx::query_as::<_, AttributeFacetCount>(query);
+            for value in values.iter().cloned() {
+                query_builder = match value {
+                    FilterValue::Uuid(value) => query_builder.bind(value),
+                    FilterValue::Decimal(value) => query_builder.bind(value),
+                    FilterValue::Text(value) => query_builder.bind(value),
+                    FilterValue::TextArray(value) => query_builder.bind(value),
+                    FilterValue::Timestamp(value) => query_builder.bind(value),
+                    FilterValue::UuidArray(value) => query_builder.bind(value),
+                    FilterValue::Bool(value) => query_builder.bind(value),
+                };
+            }
+            async move { query_builder.fetch_all(&pool).await }
+        })
+        .await?;
+        
+        Ok(counts)
+    }
+}
+
+/// Value bound to a product filter condition
+#[derive(Clone)]
+enum FilterValue {
+    Uuid(Uuid),
+    Decimal(rust_decimal::Decimal),
+    Text(String),
+    TextArray(Vec<String>),
+    Timestamp(chrono::DateTime<chrono::Utc>),
+    UuidArray(Vec<Uuid>),
+    Bool(bool),
+}
+
+/// Conditions of a product filter with the values they bind from `$1` on
+#[derive(Default)]
+struct FilterClause {
+    conditions: String,
+    values: Vec<FilterValue>,
+    /// ORDER BY of the collection filtered by, if any
+    order_by: Option<String>,
+}
+
+impl FilterClause {
+    /// Add a value to bind; its parameter number
+    fn bind(&mut self, value: FilterValue) -> usize {
+        self.values.push(value);
+        self.values.len()
+    }
+}
+
+#[async_trait]
+impl ProductRepositoryTrait for PostgresProductRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Product>> {
+        let product = sqlx::query_as::<_, Product>(
+            "SELECT * FROM products WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(id)
+        .fetch_optional(self.db.pool())
+        .await?;
+        
+        Ok(product)
+    }
+    
+    async fn list(&self) -> Result<Vec<Product>> {
+        let products = self.db.read(|pool| async move {
+            sqlx::query_as::<_, Product>(
+                "SELECT * FROM products WHERE deleted_at IS NULL ORDER BY created_at DESC"
+            )
+            .fetch_all(&pool)
+            .await
+        })
+        .await?;
+        
+        Ok(products)
+    }
+    
+    async fn find_with_filter(
+        &self,
+        filter: &ProductFilter,
+        pagination: &Pagination,
+        sort: Option<&SortParams>,
+    ) -> Result<Vec<Product>> {
+        let clause = self.filter_clause(filter).await?;
+        let mut query = format!("SELECT * FROM products WHERE deleted_at IS NULL{}", clause.conditions);
         
         // Add sorting - validate sort field against whitelist
         if let Some(sort) = sort {
@@ -246,32 +626,79 @@ impl ProductRepositoryTrait for PostgresProductRepository {
             // This is safe because we've validated it against a whitelist
             query.push_str(&format!(" ORDER BY {} {}", sort.field, direction));
         } else {
-            query.push_str(" ORDER BY created_at DESC");
+            // A collection's own sort order, or newest first
+            let order_by = clause.order_by.as_deref().unwrap_or("created_at DESC");
+            query.push_str(&format!(" ORDER BY {}", order_by));
         }
         
         // Add pagination
-        let limit_idx = param_count + 1;
-        let offset_idx = param_count + 2;
+        let limit_idx = clause.values.len() + 1;
+        let offset_idx = clause.values.len() + 2;
         query.push_str(&format!(" LIMIT ${} OFFSET ${}", limit_idx, offset_idx));
         
-        // Build query with explicit binds
-        let mut query_builder = sqlx::query_as::<_, Product>(&query);
+        // Build query with explicit binds; listings can run on a read replica
+        let (query, values) = (query.as_str(), clause.values.as_slice());
+        let products = self.db.read(move |pool| {
+            let mut query_builder = sqlx::query_as::<_, Product>(query);
+            for value in values.iter().cloned() {
+                query_builder = match value {
+                    FilterValue::Uuid(value) => query_builder.bind(value),
+                    FilterValue::Decimal(value) => query_builder.bind(value),
+                    FilterValue::Text(value) => query_builder.bind(value),
+                    FilterValue::TextArray(value) => query_builder.bind(value),
+                    FilterValue::Timestamp(value) => query_builder.bind(value),
+                    FilterValue::UuidArray(value) => query_builder.bind(value),
+                    FilterValue::Bool(value) => query_builder.bind(value),
+                };
+            }
+            query_builder = query_builder.bind(pagination.per_page);
+            query_builder = query_builder.bind(pagination.offset());
+            async move { query_builder.fetch_all(&pool).await }
+        })
+        .await?;
         
-        if let Some(category_id) = filter.category_id {
-            query_builder = query_builder.bind(category_id);
-        }
-        if let Some(price_min) = filter.price_min {
-            query_builder = query_builder.bind(price_min);
+        Ok(products)
+    }
+    
+    async fn find_after(
+        &self,
+        filter: &ProductFilter,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Product>> {
+        let mut clause = self.filter_clause(filter).await?;
+        if clause.order_by.is_some() {
+            return Err(crate::Error::validation("Collection listings are paged with `page`, not `cursor`"));
         }
-        if let Some(price_max) = filter.price_max {
-            query_builder = query_builder.bind(price_max);
+        if let Some(after) = after {
+            let param = clause.bind(FilterValue::Timestamp(after.created_at));
+            clause.bind(FilterValue::Uuid(after.id));
+            clause.conditions.push_str(&format!(" AND {}", after_cursor_sql("created_at", "id", param)));
         }
-        query_builder = query_builder.bind(pagination.per_page);
-        query_builder = query_builder.bind(pagination.offset());
+        let query = format!(
+            "SELECT * FROM products WHERE deleted_at IS NULL{} ORDER BY created_at DESC, id DESC LIMIT ${}",
+            clause.conditions,
+            clause.values.len() + 1
+        );
         
-        let products = query_builder
-            .fetch_all(self.db.pool())
-            .await?;
+        let (query, values) = (query.as_str(), clause.values.as_slice());
+        let products = self.db.read(move |pool| {
+            let mut query_builder = sqlx::query_as::<_, Product>(query);
+            for value in values.iter().cloned() {
+                query_builder = match value {
+                    FilterValue::Uuid(value) => query_builder.bind(value),
+                    FilterValue::Decimal(value) => query_builder.bind(value),
+                    FilterValue::Text(value) => query_builder.bind(value),
+                    FilterValue::TextArray(value) => query_builder.bind(value),
+                    FilterValue::Timestamp(value) => query_builder.bind(value),
+                    FilterValue::UuidArray(value) => query_builder.bind(value),
+                    FilterValue::Bool(value) => query_builder.bind(value),
+                };
+            }
+            query_builder = query_builder.bind(limit);
+            async move { query_builder.fetch_all(&pool).await }
+        })
+        .await?;
         
         Ok(products)
     }
@@ -281,26 +708,27 @@ impl ProductRepositoryTrait for PostgresProductRepository {
     }
     
     async fn count_by_filter(&self, filter: &ProductFilter) -> Result<i64> {
-        let mut query = String::from("SELECT COUNT(*) FROM products WHERE 1=1");
-        
-        if let Some(_status) = filter.status {}
+        let clause = self.filter_clause(filter).await?;
+        let query = format!("SELECT COUNT(*) FROM products WHERE deleted_at IS NULL{}", clause.conditions);
         
-        if filter.category_id.is_some() {
-            query.push_str(" AND id IN (SELECT product_id FROM product_category_relations WHERE category_id = $1)");
-        }
-        
-        let row = if let Some(category_id) = filter.category_id {
-            sqlx::query(&query)
-                .bind(category_id)
-                .fetch_one(self.db.pool())
-                .await?
-        } else {
-            sqlx::query(&query)
-                .fetch_one(self.db.pool())
-                .await?
-        };
+        let (query, values) = (query.as_str(), clause.values.as_slice());
+        let count = self.db.read(move |pool| {
+            let mut query_builder = sqlx::query_scalar::<_, i64>(query);
+            for value in values.iter().cloned() {
+                query_builder = match value {
+                    FilterValue::Uuid(value) => query_builder.bind(value),
+                    FilterValue::Decimal(value) => query_builder.bind(value),
+                    FilterValue::Text(value) => query_builder.bind(value),
+                    FilterValue::TextArray(value) => query_builder.bind(value),
+                    FilterValue::Timestamp(value) => query_builder.bind(value),
+                    FilterValue::UuidArray(value) => query_builder.bind(value),
+                    FilterValue::Bool(value) => query_builder.bind(value),
+                };
+            }
+            async move { query_builder.fetch_one(&pool).await }
+        })
+        .await?;
         
-        let count: i64 = row.get(0);
         Ok(count)
     }
     
@@ -313,7 +741,7 @@ impl ProductRepositoryTrait for PostgresProductRepository {
     }
     
     async fn delete(&self, id: Uuid) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM products WHERE id = $1")
+        let result = sqlx::query("UPDATE products SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .execute(self.db.pool())
             .await?;
@@ -0,0 +1,90 @@
+//! Price history repository for Omnibus Directive compliance
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{PriceHistoryEntry, PriceHistoryFilter},
+};
+
+/// Repository trait for price history (rows are written by database triggers)
+#[async_trait]
+pub trait PriceHistoryRepository: Send + Sync {
+    /// List a product's price changes, newest first
+    async fn history(&self, product_id: Uuid, filter: &PriceHistoryFilter, limit: i64, offset: i64) -> Result<Vec<PriceHistoryEntry>>;
+
+    /// Lowest price in the `days` before the current price took effect
+    async fn lowest_prior_price(&self, product_id: Uuid, variant_id: Option<Uuid>, days: i32) -> Result<Option<Decimal>>;
+
+    /// Lowest prior price for several products' own prices at once
+    async fn lowest_prior_prices(&self, product_ids: &[Uuid], days: i32) -> Result<HashMap<Uuid, Decimal>>;
+}
+
+/// PostgreSQL implementation of PriceHistoryRepository
+pub struct PostgresPriceHistoryRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPriceHistoryRepository {
+    /// Create a new PostgreSQL price history repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl PriceHistoryRepository for PostgresPriceHistoryRepository {
+    async fn history(&self, product_id: Uuid, filter: &PriceHistoryFilter, limit: i64, offset: i64) -> Result<Vec<PriceHistoryEntry>> {
+        sqlx::query_as::<_, PriceHistoryEntry>(
+            r#"
+            SELECT * FROM price_history
+            WHERE product_id = $1
+              AND ($2::uuid IS NULL OR variant_id = $2)
+              AND ($3::timestamptz IS NULL OR changed_at >= $3)
+              AND ($4::timestamptz IS NULL OR changed_at <= $4)
+            ORDER BY changed_at DESC
+            LIMIT $5 OFFSET $6
+            "#
+        )
+        .bind(product_id)
+        .bind(filter.variant_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list price history: {}", e)))
+    }
+
+    async fn lowest_prior_price(&self, product_id: Uuid, variant_id: Option<Uuid>, days: i32) -> Result<Option<Decimal>> {
+        sqlx::query_scalar::<_, Option<Decimal>>("SELECT lowest_prior_price($1, $2, $3)")
+            .bind(product_id)
+            .bind(variant_id)
+            .bind(days)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get lowest prior price: {}", e)))
+    }
+
+    async fn lowest_prior_prices(&self, product_ids: &[Uuid], days: i32) -> Result<HashMap<Uuid, Decimal>> {
+        if product_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = sqlx::query_as::<_, (Uuid, Option<Decimal>)>(
+            "SELECT id, lowest_prior_price(id, NULL, $2) FROM UNNEST($1::uuid[]) AS id"
+        )
+        .bind(product_ids)
+        .bind(days)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get lowest prior prices: {}", e)))?;
+
+        Ok(rows.into_iter().filter_map(|(id, price)| price.map(|p| (id, p))).collect())
+    }
+}
@@ -0,0 +1,233 @@
+//! Price list repository
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        CreatePriceListRequest, Currency, PriceList, PriceListPrice, SetPriceListPriceRequest,
+        UpdatePriceListRequest,
+    },
+};
+
+/// A product's own (base) price
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BasePrice {
+    pub price: Decimal,
+    pub compare_at_price: Option<Decimal>,
+    pub currency: Currency,
+}
+
+/// Repository trait for price lists
+#[async_trait]
+pub trait PriceListRepository: Send + Sync {
+    /// Create a price list (`countries` already normalized)
+    async fn create(&self, request: &CreatePriceListRequest, countries: &[String]) -> Result<PriceList>;
+
+    /// Get a price list
+    async fn find(&self, id: Uuid) -> Result<Option<PriceList>>;
+
+    /// List price lists by currency and priority
+    async fn list(&self) -> Result<Vec<PriceList>>;
+
+    /// Update a price list (`countries` already normalized)
+    async fn update(&self, id: Uuid, request: &UpdatePriceListRequest, countries: Option<&[String]>) -> Result<Option<PriceList>>;
+
+    /// Set (insert or replace) a product or variant price on a list
+    async fn set_price(&self, price_list_id: Uuid, request: &SetPriceListPriceRequest) -> Result<PriceListPrice>;
+
+    /// Remove a price from a list; false if it was not there
+    async fn delete_price(&self, price_list_id: Uuid, price_id: Uuid) -> Result<bool>;
+
+    /// A list's prices
+    async fn prices(&self, price_list_id: Uuid) -> Result<Vec<PriceListPrice>>;
+
+    /// Best active list price for a product in a currency: lists limited to
+    /// the shopper's country first, then by priority, and a variant's own
+    /// price before its product's
+    async fn best_price(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        currency: Currency,
+        country: Option<&str>,
+    ) -> Result<Option<PriceListPrice>>;
+
+    /// The variant's (or else the product's) own price
+    async fn base_price(&self, product_id: Uuid, variant_id: Option<Uuid>) -> Result<Option<BasePrice>>;
+}
+
+/// PostgreSQL implementation of PriceListRepository
+pub struct PostgresPriceListRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPriceListRepository {
+    /// Create a new PostgreSQL price list repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl PriceListRepository for PostgresPriceListRepository {
+    async fn create(&self, request: &CreatePriceListRequest, countries: &[String]) -> Result<PriceList> {
+        sqlx::query_as::<_, PriceList>(
+            r#"
+            INSERT INTO price_lists (name, currency, countries, priority)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#
+        )
+        .bind(request.name.trim())
+        .bind(request.currency)
+        .bind(countries)
+        .bind(request.priority)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create price list: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<PriceList>> {
+        sqlx::query_as::<_, PriceList>("SELECT * FROM price_lists WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get price list: {}", e)))
+    }
+
+    async fn list(&self) -> Result<Vec<PriceList>> {
+        sqlx::query_as::<_, PriceList>("SELECT * FROM price_lists ORDER BY currency, priority DESC, name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list price lists: {}", e)))
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdatePriceListRequest, countries: Option<&[String]>) -> Result<Option<PriceList>> {
+        sqlx::query_as::<_, PriceList>(
+            r#"
+            UPDATE price_lists
+            SET name = COALESCE($2, name),
+                countries = COALESCE($3, countries),
+                priority = COALESCE($4, priority),
+                is_active = COALESCE($5, is_active)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.name.as_deref().map(str::trim))
+        .bind(countries)
+        .bind(request.priority)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update price list: {}", e)))
+    }
+
+    async fn set_price(&self, price_list_id: Uuid, request: &SetPriceListPriceRequest) -> Result<PriceListPrice> {
+        // Product-wide and variant prices have separate partial unique indexes
+        let conflict = if request.variant_id.is_some() {
+            "(price_list_id, product_id, variant_id) WHERE variant_id IS NOT NULL"
+        } else {
+            "(price_list_id, product_id) WHERE variant_id IS NULL"
+        };
+
+        sqlx::query_as::<_, PriceListPrice>(&format!(
+            r#"
+            INSERT INTO price_list_prices (price_list_id, product_id, variant_id, price, compare_at_price)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT {} DO UPDATE
+            SET price = EXCLUDED.price, compare_at_price = EXCLUDED.compare_at_price
+            RETURNING *
+            "#,
+            conflict
+        ))
+        .bind(price_list_id)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(request.price)
+        .bind(request.compare_at_price)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to set price list price: {}", e)))
+    }
+
+    async fn delete_price(&self, price_list_id: Uuid, price_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM price_list_prices WHERE id = $1 AND price_list_id = $2")
+            .bind(price_id)
+            .bind(price_list_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete price list price: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn prices(&self, price_list_id: Uuid) -> Result<Vec<PriceListPrice>> {
+        sqlx::query_as::<_, PriceListPrice>(
+            "SELECT * FROM price_list_prices WHERE price_list_id = $1 ORDER BY product_id, variant_id NULLS FIRST"
+        )
+        .bind(price_list_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get price list prices: {}", e)))
+    }
+
+    async fn best_price(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        currency: Currency,
+        country: Option<&str>,
+    ) -> Result<Option<PriceListPrice>> {
+        sqlx::query_as::<_, PriceListPrice>(
+            r#"
+            SELECT p.*
+            FROM price_list_prices p
+            JOIN price_lists l ON l.id = p.price_list_id
+            WHERE l.is_active
+              AND l.currency = $3
+              AND p.product_id = $1
+              AND (p.variant_id IS NULL OR p.variant_id = $2)
+              AND (cardinality(l.countries) = 0 OR $4 = ANY(l.countries))
+            ORDER BY cardinality(l.countries) > 0 DESC,
+                     l.priority DESC,
+                     p.variant_id IS NOT NULL DESC,
+                     l.created_at
+            LIMIT 1
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .bind(currency)
+        .bind(country.map(str::to_uppercase))
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve price list price: {}", e)))
+    }
+
+    async fn base_price(&self, product_id: Uuid, variant_id: Option<Uuid>) -> Result<Option<BasePrice>> {
+        let price = match variant_id {
+            Some(variant_id) => {
+                sqlx::query_as::<_, BasePrice>(
+                    "SELECT price, compare_at_price, currency FROM product_variants WHERE id = $1 AND product_id = $2"
+                )
+                .bind(variant_id)
+                .bind(product_id)
+                .fetch_optional(&self.db)
+                .await
+            }
+            None => {
+                sqlx::query_as::<_, BasePrice>("SELECT price, compare_at_price, currency FROM products WHERE id = $1")
+                    .bind(product_id)
+                    .fetch_optional(&self.db)
+                    .await
+            }
+        };
+
+        price.map_err(|e| Error::Other(format!("Failed to get product price: {}", e)))
+    }
+}
@@ -0,0 +1,301 @@
+//! Print batch repository
+//!
+//! Print batches with their merged PDFs, and the shipments they print.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{PrintBatch, PrintBatchFilter, PrintBatchStatus, PrintDocumentKind, PrintableLine, PrintableShipment},
+    repository::shipment_repository::order_ship_to,
+};
+
+/// Repository trait for print batches
+#[async_trait]
+pub trait PrintBatchRepository: Send + Sync {
+    /// Shipments not yet shipped whose label has not been printed, oldest
+    /// first, optionally leaving from the location with `location` code
+    async fn unprinted_shipments(&self, location: Option<&str>, limit: i64) -> Result<Vec<Uuid>>;
+
+    /// What the labels and packing slips of the shipments show, in the
+    /// order given; unknown shipments are left out
+    async fn printable(&self, shipment_ids: &[Uuid]) -> Result<Vec<PrintableShipment>>;
+
+    /// Store a generated batch
+    async fn create(
+        &self,
+        kind: PrintDocumentKind,
+        profile: &str,
+        shipment_ids: &[Uuid],
+        pdf: &[u8],
+        page_count: i32,
+        created_by: Option<Uuid>,
+    ) -> Result<PrintBatch>;
+
+    /// A batch with its shipments
+    async fn find(&self, id: Uuid) -> Result<Option<PrintBatch>>;
+
+    /// Batches, newest first
+    async fn list(&self, filter: &PrintBatchFilter) -> Result<Vec<PrintBatch>>;
+
+    /// The merged PDF of a batch
+    async fn pdf(&self, id: Uuid) -> Result<Option<Vec<u8>>>;
+
+    /// Mark a batch printed (stamping its labels printed) or failed; None
+    /// if it does not exist
+    async fn set_status(
+        &self,
+        id: Uuid,
+        status: PrintBatchStatus,
+        error: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PrintBatch>>;
+}
+
+/// PostgreSQL implementation of PrintBatchRepository
+pub struct PostgresPrintBatchRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPrintBatchRepository {
+    /// Create a new PostgreSQL print batch repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// Everything but the PDF
+const BATCH_COLUMNS: &str = "id, document_kind, profile, status, document_count, page_count, error, created_by, \
+    created_at, updated_at, printed_at";
+
+#[derive(sqlx::FromRow)]
+struct ShipmentRow {
+    id: Uuid,
+    order_id: Uuid,
+    order_number: String,
+    location_code: Option<String>,
+    tracking_company: Option<String>,
+    service_code: Option<String>,
+    tracking_number: Option<String>,
+    label_url: Option<String>,
+}
+
+#[async_trait]
+impl PrintBatchRepository for PostgresPrintBatchRepository {
+    async fn unprinted_shipments(&self, location: Option<&str>, limit: i64) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT f.id FROM fulfillments f
+            LEFT JOIN inventory_locations l ON l.id = f.location_id
+            WHERE f.status IN ('pending', 'processing')
+              AND f.label_url IS NOT NULL
+              AND f.label_printed_at IS NULL
+              AND ($1::TEXT IS NULL OR LOWER(l.code) = LOWER($1))
+            ORDER BY f.created_at, f.id
+            LIMIT $2
+            "#
+        )
+        .bind(location)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to select unprinted shipments: {}", e)))
+    }
+
+    async fn printable(&self, shipment_ids: &[Uuid]) -> Result<Vec<PrintableShipment>> {
+        let rows = sqlx::query_as::<_, ShipmentRow>(
+            r#"
+            SELECT f.id, f.order_id, o.order_number, l.code AS location_code, f.tracking_company,
+                   f.service_code, f.tracking_number, f.label_url
+            FROM fulfillments f
+            JOIN orders o ON o.id = f.order_id
+            LEFT JOIN inventory_locations l ON l.id = f.location_id
+            WHERE f.id = ANY($1)
+            "#
+        )
+        .bind(shipment_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipments to print: {}", e)))?;
+
+        let mut shipments = Vec::with_capacity(rows.len());
+        for id in shipment_ids {
+            let Some(row) = rows.iter().find(|row| row.id == *id) else {
+                continue;
+            };
+            let lines = sqlx::query_as::<_, PrintableLine>(
+                r#"
+                SELECT oi.title, oi.variant_title, oi.sku, fi.quantity
+                FROM fulfillment_items fi
+                JOIN order_items oi ON oi.id = fi.order_item_id
+                WHERE fi.fulfillment_id = $1
+                ORDER BY fi.created_at, fi.id
+                "#
+            )
+            .bind(row.id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list shipment items: {}", e)))?;
+
+            shipments.push(PrintableShipment {
+                shipment_id: row.id,
+                order_number: row.order_number.clone(),
+                location_code: row.location_code.clone(),
+                carrier: row.tracking_company.clone(),
+                service_code: row.service_code.clone(),
+                tracking_number: row.tracking_number.clone(),
+                label_url: row.label_url.clone(),
+                ship_to: order_ship_to(&self.db, row.order_id).await?,
+                lines,
+            });
+        }
+        Ok(shipments)
+    }
+
+    async fn create(
+        &self,
+        kind: PrintDocumentKind,
+        profile: &str,
+        shipment_ids: &[Uuid],
+        pdf: &[u8],
+        page_count: i32,
+        created_by: Option<Uuid>,
+    ) -> Result<PrintBatch> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let mut batch = sqlx::query_as::<_, PrintBatch>(&format!(
+            r#"
+            INSERT INTO print_batches (document_kind, profile, document_count, page_count, pdf, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            BATCH_COLUMNS
+        ))
+        .bind(kind)
+        .bind(profile)
+        .bind(shipment_ids.len() as i32)
+        .bind(page_count)
+        .bind(pdf)
+        .bind(created_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create print batch: {}", e)))?;
+
+        for (position, shipment_id) in shipment_ids.iter().enumerate() {
+            sqlx::query("INSERT INTO print_batch_items (batch_id, shipment_id, position) VALUES ($1, $2, $3)")
+                .bind(batch.id)
+                .bind(shipment_id)
+                .bind(position as i32)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to add shipment to print batch: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit print batch: {}", e)))?;
+        batch.shipment_ids = shipment_ids.to_vec();
+        Ok(batch)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<PrintBatch>> {
+        let batch = sqlx::query_as::<_, PrintBatch>(&format!(
+            "SELECT {} FROM print_batches WHERE id = $1",
+            BATCH_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get print batch: {}", e)))?;
+        let Some(mut batch) = batch else {
+            return Ok(None);
+        };
+
+        batch.shipment_ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT shipment_id FROM print_batch_items WHERE batch_id = $1 ORDER BY position"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list print batch shipments: {}", e)))?;
+        Ok(Some(batch))
+    }
+
+    async fn list(&self, filter: &PrintBatchFilter) -> Result<Vec<PrintBatch>> {
+        sqlx::query_as::<_, PrintBatch>(&format!(
+            r#"
+            SELECT {} FROM print_batches
+            WHERE ($1::print_batch_status IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            BATCH_COLUMNS
+        ))
+        .bind(filter.status)
+        .bind(filter.limit.unwrap_or(50))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list print batches: {}", e)))
+    }
+
+    async fn pdf(&self, id: Uuid) -> Result<Option<Vec<u8>>> {
+        sqlx::query_scalar::<_, Vec<u8>>("SELECT pdf FROM print_batches WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get print batch PDF: {}", e)))
+    }
+
+    async fn set_status(
+        &self,
+        id: Uuid,
+        status: PrintBatchStatus,
+        error: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PrintBatch>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let printed_at = (status == PrintBatchStatus::Printed).then_some(now);
+        let kind = sqlx::query_scalar::<_, PrintDocumentKind>(
+            r#"
+            UPDATE print_batches
+            SET status = $2, error = $3, printed_at = COALESCE($4, printed_at), updated_at = $5
+            WHERE id = $1
+            RETURNING document_kind
+            "#
+        )
+        .bind(id)
+        .bind(status)
+        .bind(error)
+        .bind(printed_at)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update print batch: {}", e)))?;
+        let Some(kind) = kind else {
+            return Ok(None);
+        };
+
+        if let (Some(printed_at), PrintDocumentKind::ShippingLabel) = (printed_at, kind) {
+            sqlx::query(
+                r#"
+                UPDATE fulfillments SET label_printed_at = $2, updated_at = $2
+                WHERE id IN (SELECT shipment_id FROM print_batch_items WHERE batch_id = $1)
+                "#
+            )
+            .bind(id)
+            .bind(printed_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to mark labels printed: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit print batch: {}", e)))?;
+        self.find(id).await
+    }
+}
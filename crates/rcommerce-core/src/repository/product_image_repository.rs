@@ -0,0 +1,207 @@
+//! Product image repository
+
+use async_trait::async_trait;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{ImageVariant, ProductImage, UpdateProductImageRequest},
+};
+
+/// A stored product image, ready to insert
+#[derive(Debug, Clone)]
+pub struct NewProductImage {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub src: String,
+    pub alt_text: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub storage_key: String,
+    pub content_type: String,
+    pub file_size: i64,
+    pub variants: Vec<ImageVariant>,
+    pub source_url: Option<String>,
+}
+
+/// Repository trait for product images
+#[async_trait]
+pub trait ProductImageRepository: Send + Sync {
+    /// A product's images by position
+    async fn list(&self, product_id: Uuid) -> Result<Vec<ProductImage>>;
+
+    async fn find(&self, product_id: Uuid, image_id: Uuid) -> Result<Option<ProductImage>>;
+
+    /// The image of a product downloaded from `source_url`, if any
+    async fn find_by_source_url(&self, product_id: Uuid, source_url: &str) -> Result<Option<ProductImage>>;
+
+    /// Whether the product exists
+    async fn product_exists(&self, product_id: Uuid) -> Result<bool>;
+
+    /// Insert an image after the product's other images
+    async fn create(&self, image: &NewProductImage) -> Result<ProductImage>;
+
+    /// None if the product has no such image
+    async fn update(&self, product_id: Uuid, image_id: Uuid, request: &UpdateProductImageRequest) -> Result<Option<ProductImage>>;
+
+    /// Delete an image; the deleted image, None if there was none
+    async fn delete(&self, product_id: Uuid, image_id: Uuid) -> Result<Option<ProductImage>>;
+
+    /// Number the listed images first, in the given order, and the rest
+    /// after them in their current order; IDs of other products' images
+    /// are ignored
+    async fn reorder(&self, product_id: Uuid, image_ids: &[Uuid]) -> Result<Vec<ProductImage>>;
+}
+
+/// PostgreSQL implementation of ProductImageRepository
+pub struct PostgresProductImageRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresProductImageRepository {
+    /// Create a new PostgreSQL product image repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+fn variant_error(e: sqlx::Error, action: &str) -> Error {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+            Error::validation("Variant does not exist")
+        }
+        e => Error::Other(format!("Failed to {} product image: {}", action, e)),
+    }
+}
+
+#[async_trait]
+impl ProductImageRepository for PostgresProductImageRepository {
+    async fn list(&self, product_id: Uuid) -> Result<Vec<ProductImage>> {
+        sqlx::query_as::<_, ProductImage>(
+            "SELECT * FROM product_images WHERE product_id = $1 ORDER BY position, created_at"
+        )
+        .bind(product_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list product images: {}", e)))
+    }
+
+    async fn find(&self, product_id: Uuid, image_id: Uuid) -> Result<Option<ProductImage>> {
+        sqlx::query_as::<_, ProductImage>("SELECT * FROM product_images WHERE product_id = $1 AND id = $2")
+            .bind(product_id)
+            .bind(image_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get product image: {}", e)))
+    }
+
+    async fn find_by_source_url(&self, product_id: Uuid, source_url: &str) -> Result<Option<ProductImage>> {
+        sqlx::query_as::<_, ProductImage>(
+            "SELECT * FROM product_images WHERE product_id = $1 AND source_url = $2 LIMIT 1"
+        )
+        .bind(product_id)
+        .bind(source_url)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get product image by source URL: {}", e)))
+    }
+
+    async fn product_exists(&self, product_id: Uuid) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM products WHERE id = $1)")
+            .bind(product_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to check product: {}", e)))
+    }
+
+    async fn create(&self, image: &NewProductImage) -> Result<ProductImage> {
+        sqlx::query_as::<_, ProductImage>(
+            r#"
+            INSERT INTO product_images (
+                id, product_id, variant_id, position, src, alt_text, width, height,
+                storage_key, content_type, file_size, variants, source_url
+            )
+            VALUES (
+                $1, $2, $3,
+                COALESCE((SELECT MAX(position) + 1 FROM product_images WHERE product_id = $2), 0),
+                $4, $5, $6, $7, $8, $9, $10, $11, $12
+            )
+            RETURNING *
+            "#
+        )
+        .bind(image.id)
+        .bind(image.product_id)
+        .bind(image.variant_id)
+        .bind(&image.src)
+        .bind(&image.alt_text)
+        .bind(image.width)
+        .bind(image.height)
+        .bind(&image.storage_key)
+        .bind(&image.content_type)
+        .bind(image.file_size)
+        .bind(Json(&image.variants))
+        .bind(&image.source_url)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| variant_error(e, "create"))
+    }
+
+    async fn update(&self, product_id: Uuid, image_id: Uuid, request: &UpdateProductImageRequest) -> Result<Option<ProductImage>> {
+        sqlx::query_as::<_, ProductImage>(
+            r#"
+            UPDATE product_images
+            SET alt_text = COALESCE($3, alt_text),
+                variant_id = CASE WHEN $4 THEN $5 ELSE variant_id END,
+                updated_at = NOW()
+            WHERE product_id = $1 AND id = $2
+            RETURNING *
+            "#
+        )
+        .bind(product_id)
+        .bind(image_id)
+        .bind(&request.alt_text)
+        .bind(request.variant_id.is_some())
+        .bind(request.variant_id.flatten())
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| variant_error(e, "update"))
+    }
+
+    async fn delete(&self, product_id: Uuid, image_id: Uuid) -> Result<Option<ProductImage>> {
+        sqlx::query_as::<_, ProductImage>(
+            "DELETE FROM product_images WHERE product_id = $1 AND id = $2 RETURNING *"
+        )
+        .bind(product_id)
+        .bind(image_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to delete product image: {}", e)))
+    }
+
+    async fn reorder(&self, product_id: Uuid, image_ids: &[Uuid]) -> Result<Vec<ProductImage>> {
+        sqlx::query(
+            r#"
+            UPDATE product_images AS pi
+            SET position = ordered.position - 1, updated_at = NOW()
+            FROM (
+                SELECT pi.id, ROW_NUMBER() OVER (
+                    ORDER BY ids.n NULLS LAST, pi.position, pi.created_at
+                ) AS position
+                FROM product_images pi
+                LEFT JOIN UNNEST($2::UUID[]) WITH ORDINALITY AS ids(id, n) ON ids.id = pi.id
+                WHERE pi.product_id = $1
+            ) AS ordered
+            WHERE pi.id = ordered.id AND pi.position <> ordered.position - 1
+            "#
+        )
+        .bind(product_id)
+        .bind(image_ids)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to reorder product images: {}", e)))?;
+
+        self.list(product_id).await
+    }
+}
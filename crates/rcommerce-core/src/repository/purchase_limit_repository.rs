@@ -0,0 +1,360 @@
+//! Purchase limit repository
+//!
+//! Purchase limit rules, allocation lists and their members, and the past
+//! purchases the rules count.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        AllocationList, AllocationListMember, Buyer, CreateAllocationListRequest,
+        CreatePurchaseLimitRuleRequest, PurchaseLimitRule, UpdatePurchaseLimitRuleRequest,
+    },
+};
+
+/// Repository trait for purchase limits and allocation lists
+#[async_trait]
+pub trait PurchaseLimitRepository: Send + Sync {
+    /// Insert a rule
+    async fn create_rule(&self, request: &CreatePurchaseLimitRuleRequest, created_by: Option<Uuid>) -> Result<PurchaseLimitRule>;
+
+    /// Get a rule
+    async fn find_rule(&self, id: Uuid) -> Result<Option<PurchaseLimitRule>>;
+
+    /// Rules, newest first, optionally for one product
+    async fn list_rules(&self, product_id: Option<Uuid>) -> Result<Vec<PurchaseLimitRule>>;
+
+    /// Change a rule; None if it does not exist
+    async fn update_rule(&self, id: Uuid, request: &UpdatePurchaseLimitRuleRequest) -> Result<Option<PurchaseLimitRule>>;
+
+    /// Delete a rule; false if it did not exist
+    async fn delete_rule(&self, id: Uuid) -> Result<bool>;
+
+    /// Active rules on any of `product_ids`
+    async fn active_rules(&self, product_ids: &[Uuid]) -> Result<Vec<PurchaseLimitRule>>;
+
+    /// Units covered by `rule` that `buyer` ordered since `since` (ever if None),
+    /// leaving out cancelled and refunded orders
+    async fn purchased(&self, rule: &PurchaseLimitRule, buyer: &Buyer, since: Option<DateTime<Utc>>) -> Result<i64>;
+
+    /// Remember the household an order was shipped to
+    async fn set_household(&self, order_id: Uuid, household_key: &str) -> Result<()>;
+
+    /// Insert a list
+    async fn create_list(
+        &self,
+        request: &CreateAllocationListRequest,
+        opens_at: DateTime<Utc>,
+        created_by: Option<Uuid>,
+    ) -> Result<AllocationList>;
+
+    /// Get a list
+    async fn find_list(&self, id: Uuid) -> Result<Option<AllocationList>>;
+
+    /// Lists, newest first, optionally for one product
+    async fn list_lists(&self, product_id: Option<Uuid>) -> Result<Vec<AllocationList>>;
+
+    /// Delete a list and its members; false if it did not exist
+    async fn delete_list(&self, id: Uuid) -> Result<bool>;
+
+    /// Active lists on any of `product_ids` that are not public yet at `now`
+    async fn restricted_lists(&self, product_ids: &[Uuid], now: DateTime<Utc>) -> Result<Vec<AllocationList>>;
+
+    /// Which of `list_ids` the customer is a member of
+    async fn member_of(&self, customer_id: Uuid, list_ids: &[Uuid]) -> Result<Vec<Uuid>>;
+
+    /// Add customers by id or email; returns how many were not members yet
+    async fn add_members(&self, list_id: Uuid, customer_ids: &[Uuid], emails: &[String]) -> Result<u64>;
+
+    /// Remove a customer; false if they were not a member
+    async fn remove_member(&self, list_id: Uuid, customer_id: Uuid) -> Result<bool>;
+
+    /// Members, most recently added first
+    async fn members(&self, list_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AllocationListMember>>;
+
+    /// Number of members
+    async fn member_count(&self, list_id: Uuid) -> Result<i64>;
+}
+
+/// PostgreSQL implementation of PurchaseLimitRepository
+pub struct PostgresPurchaseLimitRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPurchaseLimitRepository {
+    /// Create a new PostgreSQL purchase limit repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+fn reference_error(what: &str) -> impl FnOnce(sqlx::Error) -> Error + '_ {
+    move |e| match e {
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+            Error::validation("Product or variant not found")
+        }
+        e => Error::Other(format!("Failed to create {}: {}", what, e)),
+    }
+}
+
+#[async_trait]
+impl PurchaseLimitRepository for PostgresPurchaseLimitRepository {
+    async fn create_rule(&self, request: &CreatePurchaseLimitRuleRequest, created_by: Option<Uuid>) -> Result<PurchaseLimitRule> {
+        sqlx::query_as::<_, PurchaseLimitRule>(
+            r#"
+            INSERT INTO purchase_limit_rules (name, product_id, variant_id, max_quantity, window_hours,
+                                              per_household, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(&request.name)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(request.max_quantity)
+        .bind(request.window_hours)
+        .bind(request.per_household)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(reference_error("purchase limit rule"))
+    }
+
+    async fn find_rule(&self, id: Uuid) -> Result<Option<PurchaseLimitRule>> {
+        sqlx::query_as::<_, PurchaseLimitRule>("SELECT * FROM purchase_limit_rules WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase limit rule: {}", e)))
+    }
+
+    async fn list_rules(&self, product_id: Option<Uuid>) -> Result<Vec<PurchaseLimitRule>> {
+        sqlx::query_as::<_, PurchaseLimitRule>(
+            r#"
+            SELECT * FROM purchase_limit_rules
+            WHERE ($1::uuid IS NULL OR product_id = $1)
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(product_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list purchase limit rules: {}", e)))
+    }
+
+    async fn update_rule(&self, id: Uuid, request: &UpdatePurchaseLimitRuleRequest) -> Result<Option<PurchaseLimitRule>> {
+        sqlx::query_as::<_, PurchaseLimitRule>(
+            r#"
+            UPDATE purchase_limit_rules
+            SET name = COALESCE($2, name),
+                max_quantity = COALESCE($3, max_quantity),
+                is_active = COALESCE($4, is_active)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.name.as_deref())
+        .bind(request.max_quantity)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update purchase limit rule: {}", e)))
+    }
+
+    async fn delete_rule(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM purchase_limit_rules WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete purchase limit rule: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn active_rules(&self, product_ids: &[Uuid]) -> Result<Vec<PurchaseLimitRule>> {
+        sqlx::query_as::<_, PurchaseLimitRule>(
+            "SELECT * FROM purchase_limit_rules WHERE is_active AND product_id = ANY($1)"
+        )
+        .bind(product_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase limit rules: {}", e)))
+    }
+
+    async fn purchased(&self, rule: &PurchaseLimitRule, buyer: &Buyer, since: Option<DateTime<Utc>>) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COALESCE(SUM(i.quantity), 0)::BIGINT
+            FROM orders o
+            JOIN order_items i ON i.order_id = o.id
+            WHERE i.product_id = $1
+              AND ($2::uuid IS NULL OR i.variant_id = $2)
+              AND o.status NOT IN ('cancelled', 'refunded')
+              AND NOT o.draft
+              AND ($3::timestamptz IS NULL OR o.created_at >= $3)
+              AND (o.customer_id = $4::uuid
+                   OR lower(o.email) = lower($5::text)
+                   OR ($6 AND o.household_key = $7::text))
+            "#
+        )
+        .bind(rule.product_id)
+        .bind(rule.variant_id)
+        .bind(since)
+        .bind(buyer.customer_id)
+        .bind(buyer.email.as_deref())
+        .bind(rule.per_household)
+        .bind(buyer.household_key.as_deref())
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to count purchases: {}", e)))
+    }
+
+    async fn set_household(&self, order_id: Uuid, household_key: &str) -> Result<()> {
+        sqlx::query("UPDATE orders SET household_key = $2 WHERE id = $1")
+            .bind(order_id)
+            .bind(household_key)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record order household: {}", e)))?;
+        Ok(())
+    }
+
+    async fn create_list(
+        &self,
+        request: &CreateAllocationListRequest,
+        opens_at: DateTime<Utc>,
+        created_by: Option<Uuid>,
+    ) -> Result<AllocationList> {
+        sqlx::query_as::<_, AllocationList>(
+            r#"
+            INSERT INTO allocation_lists (name, product_id, variant_id, opens_at, public_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(&request.name)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(opens_at)
+        .bind(request.public_at)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(reference_error("allocation list"))
+    }
+
+    async fn find_list(&self, id: Uuid) -> Result<Option<AllocationList>> {
+        sqlx::query_as::<_, AllocationList>("SELECT * FROM allocation_lists WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get allocation list: {}", e)))
+    }
+
+    async fn list_lists(&self, product_id: Option<Uuid>) -> Result<Vec<AllocationList>> {
+        sqlx::query_as::<_, AllocationList>(
+            r#"
+            SELECT * FROM allocation_lists
+            WHERE ($1::uuid IS NULL OR product_id = $1)
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(product_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list allocation lists: {}", e)))
+    }
+
+    async fn delete_list(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM allocation_lists WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete allocation list: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restricted_lists(&self, product_ids: &[Uuid], now: DateTime<Utc>) -> Result<Vec<AllocationList>> {
+        sqlx::query_as::<_, AllocationList>(
+            r#"
+            SELECT * FROM allocation_lists
+            WHERE is_active AND product_id = ANY($1) AND (public_at IS NULL OR public_at > $2)
+            "#
+        )
+        .bind(product_ids)
+        .bind(now)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get allocation lists: {}", e)))
+    }
+
+    async fn member_of(&self, customer_id: Uuid, list_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT list_id FROM allocation_list_members WHERE customer_id = $1 AND list_id = ANY($2)"
+        )
+        .bind(customer_id)
+        .bind(list_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to check allocation lists: {}", e)))
+    }
+
+    async fn add_members(&self, list_id: Uuid, customer_ids: &[Uuid], emails: &[String]) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO allocation_list_members (list_id, customer_id)
+            SELECT $1, c.id FROM customers c
+            WHERE c.id = ANY($2) OR lower(c.email) = ANY($3)
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(list_id)
+        .bind(customer_ids)
+        .bind(emails.iter().map(|email| email.trim().to_lowercase()).collect::<Vec<_>>())
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to add allocation list members: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn remove_member(&self, list_id: Uuid, customer_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM allocation_list_members WHERE list_id = $1 AND customer_id = $2")
+            .bind(list_id)
+            .bind(customer_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to remove allocation list member: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn members(&self, list_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AllocationListMember>> {
+        sqlx::query_as::<_, AllocationListMember>(
+            r#"
+            SELECT m.customer_id, c.email, COALESCE(c.first_name, '') AS first_name,
+                   COALESCE(c.last_name, '') AS last_name, m.added_at
+            FROM allocation_list_members m
+            JOIN customers c ON c.id = m.customer_id
+            WHERE m.list_id = $1
+            ORDER BY m.added_at DESC, m.customer_id
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(list_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list allocation list members: {}", e)))
+    }
+
+    async fn member_count(&self, list_id: Uuid) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM allocation_list_members WHERE list_id = $1")
+            .bind(list_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to count allocation list members: {}", e)))
+    }
+}
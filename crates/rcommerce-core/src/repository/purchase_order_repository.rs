@@ -0,0 +1,824 @@
+//! Purchase order repository
+//!
+//! Suppliers, the products they sell and purchase orders. Placing,
+//! receiving and cancelling an order lock it and change the stock levels
+//! of its location in one transaction; receiving also records an `in`
+//! stock movement for each line delivered.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    inventory::purchasing::{
+        CreatePurchaseOrderRequest, CreateSupplierRequest, LowStockLevel, PurchaseOrder, PurchaseOrderItem,
+        PurchaseOrderItemInput, PurchaseOrderStatus, ReceivedItem, Supplier, SupplierProduct,
+        SupplierProductRequest, UpdateSupplierRequest,
+    },
+    models::CustomerRole,
+};
+
+/// Repository trait for suppliers and purchase orders
+#[async_trait]
+pub trait PurchaseOrderRepository: Send + Sync {
+    async fn create_supplier(&self, request: &CreateSupplierRequest) -> Result<Supplier>;
+
+    /// Update a supplier; None if it does not exist
+    async fn update_supplier(&self, id: Uuid, request: &UpdateSupplierRequest) -> Result<Option<Supplier>>;
+
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>>;
+
+    /// Suppliers by name
+    async fn list_suppliers(&self, include_inactive: bool) -> Result<Vec<Supplier>>;
+
+    /// Add a product to a supplier or replace its cost, SKU and preference;
+    /// a preferred supplier replaces the product's previous one
+    async fn set_supplier_product(&self, supplier_id: Uuid, request: &SupplierProductRequest) -> Result<SupplierProduct>;
+
+    /// What a supplier charges for a variant, or for its product
+    async fn find_supplier_product(
+        &self,
+        supplier_id: Uuid,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+    ) -> Result<Option<SupplierProduct>>;
+
+    async fn list_supplier_products(&self, supplier_id: Uuid) -> Result<Vec<SupplierProduct>>;
+
+    /// Remove a product from a supplier; false if it was not listed
+    async fn remove_supplier_product(&self, supplier_id: Uuid, id: Uuid) -> Result<bool>;
+
+    /// Create a draft with its lines
+    async fn create_purchase_order(
+        &self,
+        request: &CreatePurchaseOrderRequest,
+        currency: &str,
+        created_by: Option<Uuid>,
+    ) -> Result<PurchaseOrder>;
+
+    async fn find_purchase_order(&self, id: Uuid) -> Result<Option<PurchaseOrder>>;
+
+    async fn purchase_order_items(&self, id: Uuid) -> Result<Vec<PurchaseOrderItem>>;
+
+    /// Purchase orders, newest first
+    async fn list_purchase_orders(
+        &self,
+        status: Option<PurchaseOrderStatus>,
+        supplier_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<PurchaseOrder>>;
+
+    /// Change the expected date and notes of an open order, and replace a
+    /// draft's lines; None if the order is closed (or no longer a draft
+    /// when lines are given)
+    async fn update_purchase_order(
+        &self,
+        id: Uuid,
+        expected_at: Option<DateTime<Utc>>,
+        notes: Option<&str>,
+        items: Option<&[PurchaseOrderItemInput]>,
+    ) -> Result<Option<PurchaseOrder>>;
+
+    /// Place a draft, add its quantities to incoming stock and queue it for
+    /// dispatch to the supplier
+    async fn place(&self, id: Uuid) -> Result<PurchaseOrder>;
+
+    /// Receive delivered units into available stock
+    async fn receive(&self, id: Uuid, items: &[ReceivedItem], notes: Option<&str>) -> Result<PurchaseOrder>;
+
+    /// Cancel an order and take what is outstanding off incoming stock
+    async fn cancel(&self, id: Uuid) -> Result<PurchaseOrder>;
+
+    /// Forget alerts for levels back above their reorder point
+    async fn clear_low_stock_alerts(&self) -> Result<u64>;
+
+    /// Levels of active locations at or below their reorder point
+    async fn low_stock_levels(&self) -> Result<Vec<LowStockLevel>>;
+
+    async fn mark_low_stock_alerted(&self, level_ids: &[Uuid]) -> Result<()>;
+
+    /// Add lines to the supplier's open auto-drafted order for the
+    /// location, or draft a new one; returns the order's ID
+    async fn add_to_draft(
+        &self,
+        supplier_id: Uuid,
+        location_id: Uuid,
+        currency: &str,
+        expected_at: DateTime<Utc>,
+        items: &[PurchaseOrderItemInput],
+    ) -> Result<Uuid>;
+
+    /// IDs and emails of staff with any of the roles
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>>;
+}
+
+/// PostgreSQL implementation of PurchaseOrderRepository
+#[derive(Clone)]
+pub struct PostgresPurchaseOrderRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresPurchaseOrderRepository {
+    /// Create a new PostgreSQL purchase order repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+async fn insert_items(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    purchase_order_id: Uuid,
+    items: &[PurchaseOrderItemInput],
+) -> Result<()> {
+    for item in items {
+        sqlx::query(
+            r#"
+            INSERT INTO purchase_order_items
+                (purchase_order_id, product_id, variant_id, supplier_sku, quantity_ordered, unit_cost)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(purchase_order_id)
+        .bind(item.product_id)
+        .bind(item.variant_id)
+        .bind(&item.supplier_sku)
+        .bind(item.quantity)
+        .bind(item.unit_cost)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to add purchase order line: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Lock an order, checking it is in one of the statuses
+async fn lock_order(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    statuses: &[PurchaseOrderStatus],
+) -> Result<PurchaseOrder> {
+    let purchase_order = sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))?
+        .ok_or_else(|| Error::not_found("Purchase order not found"))?;
+    if !statuses.contains(&purchase_order.status) {
+        return Err(Error::validation("Purchase order changed status; try again"));
+    }
+    Ok(purchase_order)
+}
+
+/// Change a stock level's available and incoming quantities, creating the
+/// level if the product is not stocked at the location yet
+async fn change_level(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    item: &PurchaseOrderItem,
+    location_id: Uuid,
+    available: i32,
+    incoming: i32,
+    cost_per_unit: Option<Decimal>,
+) -> Result<()> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE inventory_levels
+        SET available_quantity = available_quantity + $4,
+            incoming_quantity = GREATEST(incoming_quantity + $5, 0),
+            cost_per_unit = COALESCE($6, cost_per_unit),
+            updated_at = NOW()
+        WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND location_id = $3
+        "#
+    )
+    .bind(item.product_id)
+    .bind(item.variant_id)
+    .bind(location_id)
+    .bind(available)
+    .bind(incoming)
+    .bind(cost_per_unit)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to update inventory level: {}", e)))?;
+
+    if updated.rows_affected() == 0 {
+        sqlx::query(
+            r#"
+            INSERT INTO inventory_levels
+                (product_id, variant_id, location_id, available_quantity, incoming_quantity, cost_per_unit)
+            VALUES ($1, $2, $3, $4, GREATEST($5, 0), $6)
+            "#
+        )
+        .bind(item.product_id)
+        .bind(item.variant_id)
+        .bind(location_id)
+        .bind(available)
+        .bind(incoming)
+        .bind(cost_per_unit)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create inventory level: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl PurchaseOrderRepository for PostgresPurchaseOrderRepository {
+    async fn create_supplier(&self, request: &CreateSupplierRequest) -> Result<Supplier> {
+        sqlx::query_as::<_, Supplier>(
+            r#"
+            INSERT INTO suppliers
+                (name, code, contact_name, email, phone, address, currency, lead_time_days, notes)
+            VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 'USD'), COALESCE($8, 7), $9)
+            RETURNING *
+            "#
+        )
+        .bind(request.name.trim())
+        .bind(&request.code)
+        .bind(&request.contact_name)
+        .bind(&request.email)
+        .bind(&request.phone)
+        .bind(&request.address)
+        .bind(request.currency.as_ref().map(|currency| currency.to_uppercase()))
+        .bind(request.lead_time_days)
+        .bind(&request.notes)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A supplier with this code already exists")
+            }
+            e => Error::Other(format!("Failed to create supplier: {}", e)),
+        })
+    }
+
+    async fn update_supplier(&self, id: Uuid, request: &UpdateSupplierRequest) -> Result<Option<Supplier>> {
+        sqlx::query_as::<_, Supplier>(
+            r#"
+            UPDATE suppliers SET
+                name = COALESCE($2, name),
+                code = COALESCE($3, code),
+                contact_name = COALESCE($4, contact_name),
+                email = COALESCE($5, email),
+                phone = COALESCE($6, phone),
+                address = COALESCE($7, address),
+                currency = COALESCE($8, currency),
+                lead_time_days = COALESCE($9, lead_time_days),
+                notes = COALESCE($10, notes),
+                is_active = COALESCE($11, is_active)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(request.name.as_deref().map(str::trim))
+        .bind(&request.code)
+        .bind(&request.contact_name)
+        .bind(&request.email)
+        .bind(&request.phone)
+        .bind(&request.address)
+        .bind(request.currency.as_ref().map(|currency| currency.to_uppercase()))
+        .bind(request.lead_time_days)
+        .bind(&request.notes)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A supplier with this code already exists")
+            }
+            e => Error::Other(format!("Failed to update supplier: {}", e)),
+        })
+    }
+
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>> {
+        sqlx::query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get supplier: {}", e)))
+    }
+
+    async fn list_suppliers(&self, include_inactive: bool) -> Result<Vec<Supplier>> {
+        sqlx::query_as::<_, Supplier>("SELECT * FROM suppliers WHERE is_active OR $1 ORDER BY name")
+            .bind(include_inactive)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list suppliers: {}", e)))
+    }
+
+    async fn set_supplier_product(&self, supplier_id: Uuid, request: &SupplierProductRequest) -> Result<SupplierProduct> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        if request.is_preferred {
+            sqlx::query(
+                r#"
+                UPDATE supplier_products SET is_preferred = false
+                WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND supplier_id <> $3 AND is_preferred
+                "#
+            )
+            .bind(request.product_id)
+            .bind(request.variant_id)
+            .bind(supplier_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update preferred supplier: {}", e)))?;
+        }
+
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM supplier_products
+            WHERE supplier_id = $1 AND product_id = $2 AND variant_id IS NOT DISTINCT FROM $3
+            FOR UPDATE
+            "#
+        )
+        .bind(supplier_id)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get supplier product: {}", e)))?;
+
+        let query = match existing {
+            Some(_) => r#"
+                UPDATE supplier_products SET supplier_sku = $4, unit_cost = $5, is_preferred = $6
+                WHERE supplier_id = $1 AND product_id = $2 AND variant_id IS NOT DISTINCT FROM $3
+                RETURNING *
+                "#,
+            None => r#"
+                INSERT INTO supplier_products (supplier_id, product_id, variant_id, supplier_sku, unit_cost, is_preferred)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+                "#,
+        };
+        let product = sqlx::query_as::<_, SupplierProduct>(query)
+            .bind(supplier_id)
+            .bind(request.product_id)
+            .bind(request.variant_id)
+            .bind(&request.supplier_sku)
+            .bind(request.unit_cost)
+            .bind(request.is_preferred)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+                    Error::not_found("Product or variant not found")
+                }
+                e => Error::Other(format!("Failed to save supplier product: {}", e)),
+            })?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(product)
+    }
+
+    async fn find_supplier_product(
+        &self,
+        supplier_id: Uuid,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+    ) -> Result<Option<SupplierProduct>> {
+        sqlx::query_as::<_, SupplierProduct>(
+            r#"
+            SELECT * FROM supplier_products
+            WHERE supplier_id = $1 AND product_id = $2
+              AND (variant_id IS NOT DISTINCT FROM $3 OR variant_id IS NULL)
+            ORDER BY variant_id IS NULL
+            LIMIT 1
+            "#
+        )
+        .bind(supplier_id)
+        .bind(product_id)
+        .bind(variant_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get supplier product: {}", e)))
+    }
+
+    async fn list_supplier_products(&self, supplier_id: Uuid) -> Result<Vec<SupplierProduct>> {
+        sqlx::query_as::<_, SupplierProduct>(
+            "SELECT * FROM supplier_products WHERE supplier_id = $1 ORDER BY created_at"
+        )
+        .bind(supplier_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list supplier products: {}", e)))
+    }
+
+    async fn remove_supplier_product(&self, supplier_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM supplier_products WHERE id = $1 AND supplier_id = $2")
+            .bind(id)
+            .bind(supplier_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to remove supplier product: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_purchase_order(
+        &self,
+        request: &CreatePurchaseOrderRequest,
+        currency: &str,
+        created_by: Option<Uuid>,
+    ) -> Result<PurchaseOrder> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let purchase_order = sqlx::query_as::<_, PurchaseOrder>(
+            r#"
+            INSERT INTO purchase_orders (supplier_id, location_id, currency, expected_at, notes, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(request.supplier_id)
+        .bind(request.location_id)
+        .bind(currency)
+        .bind(request.expected_at)
+        .bind(&request.notes)
+        .bind(created_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+                Error::not_found("Inventory location not found")
+            }
+            e => Error::Other(format!("Failed to create purchase order: {}", e)),
+        })?;
+
+        insert_items(&mut tx, purchase_order.id, &request.items).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order)
+    }
+
+    async fn find_purchase_order(&self, id: Uuid) -> Result<Option<PurchaseOrder>> {
+        sqlx::query_as::<_, PurchaseOrder>("SELECT * FROM purchase_orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase order: {}", e)))
+    }
+
+    async fn purchase_order_items(&self, id: Uuid) -> Result<Vec<PurchaseOrderItem>> {
+        sqlx::query_as::<_, PurchaseOrderItem>(
+            "SELECT * FROM purchase_order_items WHERE purchase_order_id = $1 ORDER BY created_at, id"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get purchase order lines: {}", e)))
+    }
+
+    async fn list_purchase_orders(
+        &self,
+        status: Option<PurchaseOrderStatus>,
+        supplier_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<PurchaseOrder>> {
+        sqlx::query_as::<_, PurchaseOrder>(
+            r#"
+            SELECT * FROM purchase_orders
+            WHERE ($1::purchase_order_status IS NULL OR status = $1)
+              AND ($2::uuid IS NULL OR supplier_id = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(status)
+        .bind(supplier_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list purchase orders: {}", e)))
+    }
+
+    async fn update_purchase_order(
+        &self,
+        id: Uuid,
+        expected_at: Option<DateTime<Utc>>,
+        notes: Option<&str>,
+        items: Option<&[PurchaseOrderItemInput]>,
+    ) -> Result<Option<PurchaseOrder>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let statuses: &[&str] = match items {
+            Some(_) => &["draft"],
+            None => &["draft", "ordered", "partially_received"],
+        };
+        let purchase_order = sqlx::query_as::<_, PurchaseOrder>(
+            r#"
+            UPDATE purchase_orders
+            SET expected_at = COALESCE($2, expected_at), notes = COALESCE($3, notes)
+            WHERE id = $1 AND status::text = ANY($4)
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(expected_at)
+        .bind(notes)
+        .bind(statuses)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update purchase order: {}", e)))?;
+
+        if let (Some(purchase_order), Some(items)) = (&purchase_order, items) {
+            sqlx::query("DELETE FROM purchase_order_items WHERE purchase_order_id = $1")
+                .bind(purchase_order.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to replace purchase order lines: {}", e)))?;
+            insert_items(&mut tx, purchase_order.id, items).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order)
+    }
+
+    async fn place(&self, id: Uuid) -> Result<PurchaseOrder> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let purchase_order = lock_order(&mut tx, id, &[PurchaseOrderStatus::Draft]).await?;
+        let items = sqlx::query_as::<_, PurchaseOrderItem>("SELECT * FROM purchase_order_items WHERE purchase_order_id = $1")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase order lines: {}", e)))?;
+        if items.is_empty() {
+            return Err(Error::validation("Purchase order has no lines"));
+        }
+
+        for item in &items {
+            change_level(&mut tx, item, purchase_order.location_id, 0, item.quantity_ordered, None).await?;
+        }
+
+        let purchase_order = sqlx::query_as::<_, PurchaseOrder>(
+            r#"
+            UPDATE purchase_orders
+            SET status = 'ordered', ordered_at = NOW(), dispatch_status = 'pending',
+                ack_token = replace(gen_random_uuid()::text || gen_random_uuid()::text, '-', '')
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to place purchase order: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order)
+    }
+
+    async fn receive(&self, id: Uuid, items: &[ReceivedItem], notes: Option<&str>) -> Result<PurchaseOrder> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let purchase_order = lock_order(
+            &mut tx,
+            id,
+            &[PurchaseOrderStatus::Ordered, PurchaseOrderStatus::PartiallyReceived],
+        )
+        .await?;
+
+        for received in items {
+            let item = sqlx::query_as::<_, PurchaseOrderItem>(
+                r#"
+                UPDATE purchase_order_items SET quantity_received = quantity_received + $3
+                WHERE id = $1 AND purchase_order_id = $2
+                RETURNING *
+                "#
+            )
+            .bind(received.item_id)
+            .bind(id)
+            .bind(received.quantity)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.is_check_violation() => Error::validation(format!(
+                    "Line {} would receive more than was ordered",
+                    received.item_id
+                )),
+                e => Error::Other(format!("Failed to receive purchase order line: {}", e)),
+            })?
+            .ok_or_else(|| Error::not_found(format!("Line {} is not on this purchase order", received.item_id)))?;
+
+            change_level(&mut tx, &item, purchase_order.location_id, received.quantity, -received.quantity, item.unit_cost).await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO stock_movements
+                    (product_id, variant_id, location_id, quantity, movement_type, cost_per_unit, reference, notes)
+                VALUES ($1, $2, $3, $4, 'in', $5, $6, $7)
+                "#
+            )
+            .bind(item.product_id)
+            .bind(item.variant_id)
+            .bind(purchase_order.location_id)
+            .bind(received.quantity)
+            .bind(item.unit_cost)
+            .bind(format!("purchase_order:{}", purchase_order.po_number))
+            .bind(notes)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record stock movement: {}", e)))?;
+        }
+
+        let purchase_order = sqlx::query_as::<_, PurchaseOrder>(
+            r#"
+            UPDATE purchase_orders po
+            SET status = CASE WHEN complete THEN 'received' ELSE 'partially_received' END::purchase_order_status,
+                received_at = CASE WHEN complete THEN NOW() END
+            FROM (
+                SELECT bool_and(quantity_received = quantity_ordered) AS complete
+                FROM purchase_order_items WHERE purchase_order_id = $1
+            ) lines
+            WHERE po.id = $1
+            RETURNING po.*
+            "#
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update purchase order: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<PurchaseOrder> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let purchase_order = lock_order(
+            &mut tx,
+            id,
+            &[PurchaseOrderStatus::Draft, PurchaseOrderStatus::Ordered, PurchaseOrderStatus::PartiallyReceived],
+        )
+        .await?;
+
+        // Drafts never added to incoming stock
+        if purchase_order.status != PurchaseOrderStatus::Draft {
+            let items = sqlx::query_as::<_, PurchaseOrderItem>(
+                "SELECT * FROM purchase_order_items WHERE purchase_order_id = $1 AND quantity_received < quantity_ordered"
+            )
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get purchase order lines: {}", e)))?;
+
+            for item in &items {
+                change_level(&mut tx, item, purchase_order.location_id, 0, -item.outstanding(), None).await?;
+            }
+        }
+
+        let purchase_order = sqlx::query_as::<_, PurchaseOrder>(
+            "UPDATE purchase_orders SET status = 'cancelled', cancelled_at = NOW() WHERE id = $1 RETURNING *"
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to cancel purchase order: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order)
+    }
+
+    async fn clear_low_stock_alerts(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE inventory_levels SET low_stock_alerted_at = NULL
+            WHERE low_stock_alerted_at IS NOT NULL AND available_quantity > reorder_point
+            "#
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to clear low stock alerts: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn low_stock_levels(&self) -> Result<Vec<LowStockLevel>> {
+        sqlx::query_as::<_, LowStockLevel>(
+            r#"
+            SELECT il.id AS level_id, il.product_id, il.variant_id, p.title AS product_name,
+                   il.location_id, loc.name AS location_name,
+                   il.available_quantity, il.incoming_quantity, il.reorder_point, il.reorder_quantity,
+                   il.low_stock_alerted_at IS NOT NULL AS alerted,
+                   EXISTS (
+                       SELECT 1 FROM purchase_order_items poi
+                       JOIN purchase_orders po ON po.id = poi.purchase_order_id
+                       WHERE po.status = 'draft' AND po.location_id = il.location_id
+                         AND poi.product_id = il.product_id AND poi.variant_id IS NOT DISTINCT FROM il.variant_id
+                   ) AS drafted,
+                   sp.supplier_id, sp.supplier_sku, sp.unit_cost,
+                   sp.currency AS supplier_currency, sp.lead_time_days
+            FROM inventory_levels il
+            JOIN products p ON p.id = il.product_id
+            JOIN inventory_locations loc ON loc.id = il.location_id
+            -- A variant's own supplier before its product's, then the preferred one
+            LEFT JOIN LATERAL (
+                SELECT sp.supplier_id, sp.supplier_sku, sp.unit_cost, s.currency, s.lead_time_days
+                FROM supplier_products sp
+                JOIN suppliers s ON s.id = sp.supplier_id AND s.is_active
+                WHERE sp.product_id = il.product_id
+                  AND (sp.variant_id IS NOT DISTINCT FROM il.variant_id OR sp.variant_id IS NULL)
+                ORDER BY sp.variant_id IS NULL, sp.is_preferred DESC, sp.created_at
+                LIMIT 1
+            ) sp ON TRUE
+            WHERE loc.is_active AND il.reorder_point > 0 AND il.available_quantity <= il.reorder_point
+            ORDER BY loc.name, p.title
+            "#
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list low stock levels: {}", e)))
+    }
+
+    async fn mark_low_stock_alerted(&self, level_ids: &[Uuid]) -> Result<()> {
+        sqlx::query("UPDATE inventory_levels SET low_stock_alerted_at = NOW() WHERE id = ANY($1)")
+            .bind(level_ids)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record low stock alerts: {}", e)))?;
+        Ok(())
+    }
+
+    async fn add_to_draft(
+        &self,
+        supplier_id: Uuid,
+        location_id: Uuid,
+        currency: &str,
+        expected_at: DateTime<Utc>,
+        items: &[PurchaseOrderItemInput],
+    ) -> Result<Uuid> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM purchase_orders
+            WHERE supplier_id = $1 AND location_id = $2 AND status = 'draft' AND auto_drafted
+            ORDER BY created_at DESC
+            LIMIT 1
+            FOR UPDATE
+            "#
+        )
+        .bind(supplier_id)
+        .bind(location_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get draft purchase order: {}", e)))?;
+
+        let purchase_order_id = match existing {
+            Some(id) => id,
+            None => sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO purchase_orders (supplier_id, location_id, currency, expected_at, auto_drafted, notes)
+                VALUES ($1, $2, $3, $4, true, 'Drafted for low stock')
+                RETURNING id
+                "#
+            )
+            .bind(supplier_id)
+            .bind(location_id)
+            .bind(currency)
+            .bind(expected_at)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to draft purchase order: {}", e)))?,
+        };
+
+        insert_items(&mut tx, purchase_order_id, items).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(purchase_order_id)
+    }
+
+    async fn find_staff(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>> {
+        let roles: Vec<String> = roles
+            .iter()
+            .map(|role| format!("{:?}", role).to_lowercase())
+            .collect();
+
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, email FROM customers WHERE role::text = ANY($1) ORDER BY email")
+            .bind(&roles)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list staff: {}", e)))
+    }
+}
@@ -0,0 +1,262 @@
+//! Redirect repository
+//!
+//! Manual redirects, and product and category lookups by current or old
+//! slug (slug history rows are written by database triggers).
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::models::{CreateUrlRedirectRequest, SlugEntityType, SlugHistoryEntry, SlugLookup, UrlRedirect};
+use crate::{Error, Result};
+
+/// Repository trait for redirects and slug history
+#[async_trait]
+pub trait RedirectRepository: Send + Sync {
+    /// Manual redirects, by source path
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<UrlRedirect>>;
+
+    /// Every manual redirect, for the in-memory lookup table
+    async fn all(&self) -> Result<Vec<UrlRedirect>>;
+
+    async fn find(&self, id: Uuid) -> Result<Option<UrlRedirect>>;
+
+    async fn find_by_source(&self, source_path: &str) -> Result<Option<UrlRedirect>>;
+
+    async fn create(
+        &self,
+        source_path: &str,
+        target_path: &str,
+        status_code: i16,
+        created_by: Option<Uuid>,
+    ) -> Result<UrlRedirect>;
+
+    async fn update(&self, id: Uuid, target_path: &str, status_code: i16) -> Result<Option<UrlRedirect>>;
+
+    /// False if there was no such redirect
+    async fn delete(&self, id: Uuid) -> Result<bool>;
+
+    /// Add hits counted in memory, per redirect
+    async fn record_hits(&self, hits: &[(Uuid, i64)]) -> Result<()>;
+
+    /// Create redirects in one transaction, replacing the target and status
+    /// of existing sources when `overwrite` is set and skipping them
+    /// otherwise; returns the numbers created and updated
+    async fn import(
+        &self,
+        redirects: &[CreateUrlRedirectRequest],
+        overwrite: bool,
+        created_by: Option<Uuid>,
+    ) -> Result<(u64, u64)>;
+
+    /// The entity using a slug now, or else the one that used it last
+    async fn lookup_slug(&self, entity_type: SlugEntityType, slug: &str) -> Result<Option<SlugLookup>>;
+
+    /// An entity's old slugs, newest first
+    async fn slug_history(&self, entity_type: SlugEntityType, entity_id: Uuid) -> Result<Vec<SlugHistoryEntry>>;
+}
+
+/// PostgreSQL implementation of RedirectRepository
+#[derive(Clone)]
+pub struct PostgresRedirectRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresRedirectRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RedirectRepository for PostgresRedirectRepository {
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<UrlRedirect>> {
+        sqlx::query_as::<_, UrlRedirect>("SELECT * FROM url_redirects ORDER BY source_path LIMIT $1 OFFSET $2")
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list redirects: {}", e)))
+    }
+
+    async fn all(&self) -> Result<Vec<UrlRedirect>> {
+        sqlx::query_as::<_, UrlRedirect>("SELECT * FROM url_redirects")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list redirects: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<UrlRedirect>> {
+        sqlx::query_as::<_, UrlRedirect>("SELECT * FROM url_redirects WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to fetch redirect: {}", e)))
+    }
+
+    async fn find_by_source(&self, source_path: &str) -> Result<Option<UrlRedirect>> {
+        sqlx::query_as::<_, UrlRedirect>("SELECT * FROM url_redirects WHERE source_path = $1")
+            .bind(source_path)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to fetch redirect: {}", e)))
+    }
+
+    async fn create(
+        &self,
+        source_path: &str,
+        target_path: &str,
+        status_code: i16,
+        created_by: Option<Uuid>,
+    ) -> Result<UrlRedirect> {
+        sqlx::query_as::<_, UrlRedirect>(
+            r#"
+            INSERT INTO url_redirects (source_path, target_path, status_code, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(source_path)
+        .bind(target_path)
+        .bind(status_code)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation(format!("A redirect from {} already exists", source_path))
+            }
+            e => Error::Other(format!("Failed to create redirect: {}", e)),
+        })
+    }
+
+    async fn update(&self, id: Uuid, target_path: &str, status_code: i16) -> Result<Option<UrlRedirect>> {
+        sqlx::query_as::<_, UrlRedirect>(
+            r#"
+            UPDATE url_redirects
+            SET target_path = $2, status_code = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(target_path)
+        .bind(status_code)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update redirect: {}", e)))
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM url_redirects WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete redirect: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_hits(&self, hits: &[(Uuid, i64)]) -> Result<()> {
+        let (ids, counts): (Vec<Uuid>, Vec<i64>) = hits.iter().copied().unzip();
+        sqlx::query(
+            r#"
+            UPDATE url_redirects r
+            SET hits = r.hits + h.count, last_hit_at = NOW()
+            FROM UNNEST($1::uuid[], $2::bigint[]) AS h(id, count)
+            WHERE r.id = h.id
+            "#,
+        )
+        .bind(ids)
+        .bind(counts)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record redirect hits: {}", e)))?;
+        Ok(())
+    }
+
+    async fn import(
+        &self,
+        redirects: &[CreateUrlRedirectRequest],
+        overwrite: bool,
+        created_by: Option<Uuid>,
+    ) -> Result<(u64, u64)> {
+        let on_conflict = if overwrite {
+            "DO UPDATE SET target_path = EXCLUDED.target_path, status_code = EXCLUDED.status_code, updated_at = NOW()"
+        } else {
+            "DO NOTHING"
+        };
+        let sql = format!(
+            r#"
+            INSERT INTO url_redirects (source_path, target_path, status_code, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source_path) {on_conflict}
+            RETURNING (xmax = 0) AS inserted
+            "#
+        );
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        let (mut created, mut updated) = (0, 0);
+        for redirect in redirects {
+            let inserted: Option<bool> = sqlx::query_scalar(&sql)
+                .bind(&redirect.source_path)
+                .bind(&redirect.target_path)
+                .bind(redirect.status_code.unwrap_or(301))
+                .bind(created_by)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to import redirect {}: {}", redirect.source_path, e)))?;
+            match inserted {
+                Some(true) => created += 1,
+                Some(false) => updated += 1,
+                None => {}
+            }
+        }
+        tx.commit()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to commit redirect import: {}", e)))?;
+        Ok((created, updated))
+    }
+
+    async fn lookup_slug(&self, entity_type: SlugEntityType, slug: &str) -> Result<Option<SlugLookup>> {
+        let table = match entity_type {
+            SlugEntityType::Product => "products",
+            SlugEntityType::Category => "product_categories",
+        };
+        sqlx::query_as::<_, SlugLookup>(&format!(
+            r#"
+            SELECT id AS entity_id, slug FROM {table} WHERE slug = $2
+            UNION ALL
+            (
+                SELECT e.id AS entity_id, e.slug
+                FROM slug_history h
+                JOIN {table} e ON e.id = h.entity_id
+                WHERE h.entity_type = $1 AND h.slug = $2
+            )
+            LIMIT 1
+            "#
+        ))
+        .bind(entity_type)
+        .bind(slug)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to look up slug: {}", e)))
+    }
+
+    async fn slug_history(&self, entity_type: SlugEntityType, entity_id: Uuid) -> Result<Vec<SlugHistoryEntry>> {
+        sqlx::query_as::<_, SlugHistoryEntry>(
+            r#"
+            SELECT * FROM slug_history
+            WHERE entity_type = $1 AND entity_id = $2
+            ORDER BY changed_at DESC
+            "#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list slug history: {}", e)))
+    }
+}
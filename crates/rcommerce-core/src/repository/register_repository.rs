@@ -0,0 +1,293 @@
+//! POS register repository
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        CreateRegisterRequest, CustomerRole, PosRegister, RegisterSession, RegisterTransaction,
+        RegisterTransactionKind, CASH_METHOD,
+    },
+};
+
+/// Repository trait for POS registers and their sessions
+#[async_trait]
+pub trait RegisterRepository: Send + Sync {
+    /// Role of the acting user; None if the user does not exist
+    async fn actor_role(&self, actor_id: Uuid) -> Result<Option<CustomerRole>>;
+
+    /// Create a register
+    async fn create_register(&self, request: &CreateRegisterRequest) -> Result<PosRegister>;
+
+    /// Get a register
+    async fn find_register(&self, id: Uuid) -> Result<Option<PosRegister>>;
+
+    /// List registers by name
+    async fn list_registers(&self) -> Result<Vec<PosRegister>>;
+
+    /// Open a session. Returns None if the register already has an open session.
+    async fn open_session(
+        &self,
+        register: &PosRegister,
+        opening_float: Decimal,
+        opened_by: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<RegisterSession>>;
+
+    /// Get a session
+    async fn find_session(&self, id: Uuid) -> Result<Option<RegisterSession>>;
+
+    /// List a register's sessions, newest first
+    async fn list_sessions(&self, register_id: Uuid, limit: i64) -> Result<Vec<RegisterSession>>;
+
+    /// Record a transaction in an open session. Returns None if the session is closed.
+    #[allow(clippy::too_many_arguments)]
+    async fn record_transaction(
+        &self,
+        session_id: Uuid,
+        kind: RegisterTransactionKind,
+        method: &str,
+        amount: Decimal,
+        order_id: Option<Uuid>,
+        reference: Option<&str>,
+        recorded_by: Uuid,
+    ) -> Result<Option<RegisterTransaction>>;
+
+    /// A session's transactions in the order they were recorded
+    async fn transactions(&self, session_id: Uuid) -> Result<Vec<RegisterTransaction>>;
+
+    /// Close an open session with its count, storing the expected cash and
+    /// over/short. Returns None if it is already closed.
+    async fn close_session(
+        &self,
+        session_id: Uuid,
+        counted_cash: Decimal,
+        closed_by: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<RegisterSession>>;
+}
+
+/// PostgreSQL implementation of RegisterRepository
+pub struct PostgresRegisterRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresRegisterRepository {
+    /// Create a new PostgreSQL register repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RegisterRepository for PostgresRegisterRepository {
+    async fn actor_role(&self, actor_id: Uuid) -> Result<Option<CustomerRole>> {
+        sqlx::query_scalar::<_, CustomerRole>("SELECT role FROM customers WHERE id = $1")
+            .bind(actor_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get user role: {}", e)))
+    }
+
+    async fn create_register(&self, request: &CreateRegisterRequest) -> Result<PosRegister> {
+        sqlx::query_as::<_, PosRegister>(
+            "INSERT INTO pos_registers (name, location_id, currency) VALUES ($1, $2, $3) RETURNING *"
+        )
+        .bind(request.name.trim())
+        .bind(request.location_id)
+        .bind(request.currency)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create register: {}", e)))
+    }
+
+    async fn find_register(&self, id: Uuid) -> Result<Option<PosRegister>> {
+        sqlx::query_as::<_, PosRegister>("SELECT * FROM pos_registers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get register: {}", e)))
+    }
+
+    async fn list_registers(&self) -> Result<Vec<PosRegister>> {
+        sqlx::query_as::<_, PosRegister>("SELECT * FROM pos_registers ORDER BY name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list registers: {}", e)))
+    }
+
+    async fn open_session(
+        &self,
+        register: &PosRegister,
+        opening_float: Decimal,
+        opened_by: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<RegisterSession>> {
+        // The partial unique index allows one open session per register
+        sqlx::query_as::<_, RegisterSession>(
+            r#"
+            INSERT INTO register_sessions (register_id, currency, opening_float, opened_by, opening_notes)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (register_id) WHERE status = 'open' DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(register.id)
+        .bind(register.currency)
+        .bind(opening_float)
+        .bind(opened_by)
+        .bind(notes)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to open register session: {}", e)))
+    }
+
+    async fn find_session(&self, id: Uuid) -> Result<Option<RegisterSession>> {
+        sqlx::query_as::<_, RegisterSession>("SELECT * FROM register_sessions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get register session: {}", e)))
+    }
+
+    async fn list_sessions(&self, register_id: Uuid, limit: i64) -> Result<Vec<RegisterSession>> {
+        sqlx::query_as::<_, RegisterSession>(
+            "SELECT * FROM register_sessions WHERE register_id = $1 ORDER BY opened_at DESC LIMIT $2"
+        )
+        .bind(register_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list register sessions: {}", e)))
+    }
+
+    async fn record_transaction(
+        &self,
+        session_id: Uuid,
+        kind: RegisterTransactionKind,
+        method: &str,
+        amount: Decimal,
+        order_id: Option<Uuid>,
+        reference: Option<&str>,
+        recorded_by: Uuid,
+    ) -> Result<Option<RegisterTransaction>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        // Lock the session so a close cannot miss a transaction
+        let open = sqlx::query_scalar::<_, bool>(
+            "SELECT status = 'open' FROM register_sessions WHERE id = $1 FOR UPDATE"
+        )
+        .bind(session_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to lock register session: {}", e)))?;
+        if open != Some(true) {
+            return Ok(None);
+        }
+
+        let transaction = sqlx::query_as::<_, RegisterTransaction>(
+            r#"
+            INSERT INTO register_transactions (session_id, kind, method, amount, order_id, reference, recorded_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(session_id)
+        .bind(kind)
+        .bind(method)
+        .bind(amount)
+        .bind(order_id)
+        .bind(reference)
+        .bind(recorded_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record register transaction: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(Some(transaction))
+    }
+
+    async fn transactions(&self, session_id: Uuid) -> Result<Vec<RegisterTransaction>> {
+        sqlx::query_as::<_, RegisterTransaction>(
+            "SELECT * FROM register_transactions WHERE session_id = $1 ORDER BY created_at, id"
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get register transactions: {}", e)))
+    }
+
+    async fn close_session(
+        &self,
+        session_id: Uuid,
+        counted_cash: Decimal,
+        closed_by: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<RegisterSession>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let opening_float = sqlx::query_scalar::<_, Decimal>(
+            "SELECT opening_float FROM register_sessions WHERE id = $1 AND status = 'open' FOR UPDATE"
+        )
+        .bind(session_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to lock register session: {}", e)))?;
+        let Some(opening_float) = opening_float else {
+            return Ok(None);
+        };
+
+        // Same formula as ZReport::build; runs after the lock so no
+        // transaction recorded before the close is missed
+        let cash_movements = sqlx::query_scalar::<_, Decimal>(
+            r#"
+            SELECT COALESCE(SUM(CASE
+                WHEN kind IN ('sale', 'paid_in') THEN amount
+                ELSE -amount
+            END), 0)
+            FROM register_transactions
+            WHERE session_id = $1 AND method = $2
+            "#
+        )
+        .bind(session_id)
+        .bind(CASH_METHOD)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to total register cash: {}", e)))?;
+        let expected_cash = opening_float + cash_movements;
+
+        let session = sqlx::query_as::<_, RegisterSession>(
+            r#"
+            UPDATE register_sessions
+            SET status = 'closed',
+                counted_cash = $2,
+                expected_cash = $3,
+                over_short = $2 - $3,
+                closed_by = $4,
+                closed_at = NOW(),
+                closing_notes = $5
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(session_id)
+        .bind(counted_cash)
+        .bind(expected_cash)
+        .bind(closed_by)
+        .bind(notes)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to close register session: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(Some(session))
+    }
+}
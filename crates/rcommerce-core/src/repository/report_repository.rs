@@ -0,0 +1,555 @@
+//! Report repository
+//!
+//! Report subscriptions, their deliveries (with the generated files) and
+//! the queries behind each report.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::analytics::FunnelReport;
+use crate::models::{CreateReportSubscriptionRequest, NewReportDelivery, ReportDelivery, ReportSubscription};
+use crate::reports::{ReportTable, ReportValue};
+use crate::repository::Database;
+use crate::repository::{AnalyticsRepository, PostgresAnalyticsRepository};
+use crate::{Error, Result};
+
+/// Delivery columns, without the file
+const DELIVERY_COLUMNS: &str = r#"
+    id, subscription_id, report, format, period_start, period_end, recipients, status, error,
+    filename, row_count, size_bytes, content IS NOT NULL AS available, download_token, expires_at,
+    sent_count, last_sent_at, created_at
+"#;
+
+/// Repository trait for report subscriptions, deliveries and report data
+#[async_trait]
+pub trait ReportRepository: Send + Sync {
+    async fn create_subscription(
+        &self,
+        request: &CreateReportSubscriptionRequest,
+        next_run_at: DateTime<Utc>,
+        created_by: Option<Uuid>,
+    ) -> Result<ReportSubscription>;
+
+    async fn find_subscription(&self, id: Uuid) -> Result<Option<ReportSubscription>>;
+
+    async fn list_subscriptions(&self) -> Result<Vec<ReportSubscription>>;
+
+    /// Replace a subscription; None if it does not exist
+    async fn update_subscription(
+        &self,
+        id: Uuid,
+        subscription: &CreateReportSubscriptionRequest,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<Option<ReportSubscription>>;
+
+    /// Delete a subscription, keeping its deliveries; false if it did not exist
+    async fn delete_subscription(&self, id: Uuid) -> Result<bool>;
+
+    /// Active subscriptions due by `now`, most overdue first
+    async fn due_subscriptions(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<ReportSubscription>>;
+
+    async fn schedule_next(&self, id: Uuid, last_run_at: DateTime<Utc>, next_run_at: DateTime<Utc>) -> Result<()>;
+
+    async fn create_delivery(&self, delivery: &NewReportDelivery) -> Result<ReportDelivery>;
+
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<ReportDelivery>>;
+
+    async fn find_delivery_by_token(&self, token: &str) -> Result<Option<ReportDelivery>>;
+
+    /// The delivery's file; None once it has been purged
+    async fn delivery_content(&self, id: Uuid) -> Result<Option<Vec<u8>>>;
+
+    /// Deliveries, newest first
+    async fn list_deliveries(&self, subscription_id: Option<Uuid>, limit: i64) -> Result<Vec<ReportDelivery>>;
+
+    /// Count an email of the delivery to its recipients
+    async fn record_sent(&self, id: Uuid) -> Result<ReportDelivery>;
+
+    /// Mark a delivery failed, e.g. when its emails could not be queued
+    async fn record_failure(&self, id: Uuid, error: &str) -> Result<ReportDelivery>;
+
+    /// Drop the files of expired deliveries, keeping their history
+    async fn purge_expired(&self) -> Result<u64>;
+
+    /// Orders, items and revenue (in the base currency) per day
+    async fn sales_summary(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable>;
+
+    /// Taxable amounts and tax per country, region and rate
+    async fn tax_liability(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable>;
+
+    /// Inventory levels at or below their reorder point
+    async fn low_stock(&self, limit: i64) -> Result<ReportTable>;
+
+    /// Failed payments, newest first
+    async fn failed_payments(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable>;
+
+    /// Storefront sessions reaching each checkout step
+    async fn checkout_funnel(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<ReportTable>;
+}
+
+/// PostgreSQL implementation of ReportRepository
+#[derive(Clone)]
+pub struct PostgresReportRepository {
+    db: sqlx::PgPool,
+    /// Where report queries run; the primary unless read replicas are set
+    reads: Database,
+}
+
+impl PostgresReportRepository {
+    /// Create a new PostgreSQL report repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { reads: Database::new(db.clone()), db }
+    }
+
+    /// Run report queries on the read replicas of `db`
+    pub fn with_read_replicas(mut self, db: Database) -> Self {
+        self.reads = db;
+        self
+    }
+}
+
+/// Keep the first `limit` rows, flagging the table if there were more
+fn limit_rows(mut table: ReportTable, limit: i64) -> ReportTable {
+    let limit = limit.max(0) as usize;
+    if table.rows.len() > limit {
+        table.rows.truncate(limit);
+        table.truncated = true;
+    }
+    table
+}
+
+fn date(at: DateTime<Utc>) -> ReportValue {
+    at.format("%Y-%m-%d").to_string().into()
+}
+
+#[async_trait]
+impl ReportRepository for PostgresReportRepository {
+    async fn create_subscription(
+        &self,
+        request: &CreateReportSubscriptionRequest,
+        next_run_at: DateTime<Utc>,
+        created_by: Option<Uuid>,
+    ) -> Result<ReportSubscription> {
+        sqlx::query_as::<_, ReportSubscription>(
+            r#"
+            INSERT INTO report_subscriptions
+                (name, report, schedule, format, recipients, hour, weekday, day_of_month, is_active, next_run_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING *
+            "#
+        )
+        .bind(&request.name)
+        .bind(request.report)
+        .bind(request.schedule)
+        .bind(request.format)
+        .bind(&request.recipients)
+        .bind(request.hour)
+        .bind(request.weekday)
+        .bind(request.day_of_month)
+        .bind(request.is_active)
+        .bind(next_run_at)
+        .bind(created_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create report subscription: {}", e)))
+    }
+
+    async fn find_subscription(&self, id: Uuid) -> Result<Option<ReportSubscription>> {
+        sqlx::query_as::<_, ReportSubscription>("SELECT * FROM report_subscriptions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get report subscription: {}", e)))
+    }
+
+    async fn list_subscriptions(&self) -> Result<Vec<ReportSubscription>> {
+        sqlx::query_as::<_, ReportSubscription>("SELECT * FROM report_subscriptions ORDER BY name, created_at")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list report subscriptions: {}", e)))
+    }
+
+    async fn update_subscription(
+        &self,
+        id: Uuid,
+        subscription: &CreateReportSubscriptionRequest,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<Option<ReportSubscription>> {
+        sqlx::query_as::<_, ReportSubscription>(
+            r#"
+            UPDATE report_subscriptions
+            SET name = $2, report = $3, schedule = $4, format = $5, recipients = $6,
+                hour = $7, weekday = $8, day_of_month = $9, is_active = $10, next_run_at = $11
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&subscription.name)
+        .bind(subscription.report)
+        .bind(subscription.schedule)
+        .bind(subscription.format)
+        .bind(&subscription.recipients)
+        .bind(subscription.hour)
+        .bind(subscription.weekday)
+        .bind(subscription.day_of_month)
+        .bind(subscription.is_active)
+        .bind(next_run_at)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update report subscription: {}", e)))
+    }
+
+    async fn delete_subscription(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM report_subscriptions WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete report subscription: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn due_subscriptions(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<ReportSubscription>> {
+        sqlx::query_as::<_, ReportSubscription>(
+            r#"
+            SELECT * FROM report_subscriptions
+            WHERE is_active AND next_run_at <= $1
+            ORDER BY next_run_at
+            LIMIT $2
+            "#
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list due report subscriptions: {}", e)))
+    }
+
+    async fn schedule_next(&self, id: Uuid, last_run_at: DateTime<Utc>, next_run_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE report_subscriptions SET last_run_at = $2, next_run_at = $3 WHERE id = $1")
+            .bind(id)
+            .bind(last_run_at)
+            .bind(next_run_at)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to schedule report subscription: {}", e)))?;
+        Ok(())
+    }
+
+    async fn create_delivery(&self, delivery: &NewReportDelivery) -> Result<ReportDelivery> {
+        let size_bytes = delivery.content.as_ref().map_or(0, |content| content.len() as i32);
+        sqlx::query_as::<_, ReportDelivery>(&format!(
+            r#"
+            INSERT INTO report_deliveries
+                (subscription_id, report, format, period_start, period_end, recipients, status, error,
+                 filename, row_count, content, size_bytes, download_token, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING {}
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(delivery.subscription_id)
+        .bind(delivery.report)
+        .bind(delivery.format)
+        .bind(delivery.period_start)
+        .bind(delivery.period_end)
+        .bind(&delivery.recipients)
+        .bind(delivery.status)
+        .bind(&delivery.error)
+        .bind(&delivery.filename)
+        .bind(delivery.row_count)
+        .bind(&delivery.content)
+        .bind(size_bytes)
+        .bind(&delivery.download_token)
+        .bind(delivery.expires_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record report delivery: {}", e)))
+    }
+
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<ReportDelivery>> {
+        sqlx::query_as::<_, ReportDelivery>(&format!("SELECT {} FROM report_deliveries WHERE id = $1", DELIVERY_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get report delivery: {}", e)))
+    }
+
+    async fn find_delivery_by_token(&self, token: &str) -> Result<Option<ReportDelivery>> {
+        sqlx::query_as::<_, ReportDelivery>(&format!(
+            "SELECT {} FROM report_deliveries WHERE download_token = $1",
+            DELIVERY_COLUMNS
+        ))
+        .bind(token)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get report delivery: {}", e)))
+    }
+
+    async fn delivery_content(&self, id: Uuid) -> Result<Option<Vec<u8>>> {
+        let content: Option<Option<Vec<u8>>> = sqlx::query_scalar("SELECT content FROM report_deliveries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read report file: {}", e)))?;
+        Ok(content.flatten())
+    }
+
+    async fn list_deliveries(&self, subscription_id: Option<Uuid>, limit: i64) -> Result<Vec<ReportDelivery>> {
+        sqlx::query_as::<_, ReportDelivery>(&format!(
+            r#"
+            SELECT {} FROM report_deliveries
+            WHERE ($1::UUID IS NULL OR subscription_id = $1)
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(subscription_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list report deliveries: {}", e)))
+    }
+
+    async fn record_sent(&self, id: Uuid) -> Result<ReportDelivery> {
+        sqlx::query_as::<_, ReportDelivery>(&format!(
+            r#"
+            UPDATE report_deliveries
+            SET sent_count = sent_count + 1, last_sent_at = NOW(), status = 'sent', error = NULL
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record report email: {}", e)))
+    }
+
+    async fn record_failure(&self, id: Uuid, error: &str) -> Result<ReportDelivery> {
+        sqlx::query_as::<_, ReportDelivery>(&format!(
+            "UPDATE report_deliveries SET status = 'failed', error = $2 WHERE id = $1 RETURNING {}",
+            DELIVERY_COLUMNS
+        ))
+        .bind(id)
+        .bind(error)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record report failure: {}", e)))
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE report_deliveries SET content = NULL WHERE content IS NOT NULL AND expires_at <= NOW()"
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to purge report files: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn sales_summary(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable> {
+        let rows = self.reads.read(move |pool| async move {
+            sqlx::query_as::<_, (DateTime<Utc>, i64, i64, Decimal, Decimal, Decimal, Decimal, Decimal)>(
+                r#"
+                SELECT DATE_TRUNC('day', o.created_at) AS day,
+                       COUNT(*) AS orders,
+                       COALESCE(SUM((SELECT SUM(quantity) FROM order_items WHERE order_id = o.id)), 0)::BIGINT AS items_sold,
+                       COALESCE(ROUND(SUM(o.subtotal * COALESCE(o.exchange_rate, 1)), 2), 0) AS subtotal,
+                       COALESCE(ROUND(SUM(o.discount_total * COALESCE(o.exchange_rate, 1)), 2), 0) AS discounts,
+                       COALESCE(ROUND(SUM(o.shipping_total * COALESCE(o.exchange_rate, 1)), 2), 0) AS shipping,
+                       COALESCE(ROUND(SUM(o.tax_total * COALESCE(o.exchange_rate, 1)), 2), 0) AS tax,
+                       COALESCE(ROUND(SUM(o.total * COALESCE(o.exchange_rate, 1)), 2), 0) AS revenue
+                FROM orders o
+                WHERE o.created_at >= $1 AND o.created_at < $2
+                  AND o.status NOT IN ('cancelled', 'refunded')
+                GROUP BY 1
+                ORDER BY 1
+                LIMIT $3
+                "#
+            )
+            .bind(from)
+            .bind(to)
+            .bind(limit + 1)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Failed to query sales summary: {}", e)))?;
+
+        let mut table = ReportTable::new(vec![
+            "date", "orders", "items_sold", "subtotal", "discounts", "shipping", "tax", "revenue", "average_order",
+        ]);
+        for (day, orders, items_sold, subtotal, discounts, shipping, tax, revenue) in rows {
+            let average = if orders > 0 {
+                (revenue / Decimal::from(orders)).round_dp(2)
+            } else {
+                Decimal::ZERO
+            };
+            table.push(vec![
+                date(day),
+                orders.into(),
+                items_sold.into(),
+                subtotal.into(),
+                discounts.into(),
+                shipping.into(),
+                tax.into(),
+                revenue.into(),
+                average.into(),
+            ]);
+        }
+        Ok(limit_rows(table, limit))
+    }
+
+    async fn tax_liability(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable> {
+        let rows = self.reads.read(move |pool| async move {
+            sqlx::query_as::<_, (String, Option<String>, Decimal, i64, Decimal, Decimal)>(
+                r#"
+                SELECT TRIM(t.country_code) AS country_code, t.region_code, t.tax_rate,
+                       COUNT(DISTINCT t.order_id) AS orders,
+                       ROUND(SUM(t.taxable_amount), 2) AS taxable_amount,
+                       ROUND(SUM(t.tax_amount), 2) AS tax_amount
+                FROM tax_transactions t
+                JOIN orders o ON o.id = t.order_id
+                WHERE t.created_at >= $1 AND t.created_at < $2
+                  AND o.status NOT IN ('cancelled', 'refunded')
+                GROUP BY 1, 2, 3
+                ORDER BY 1, 2 NULLS FIRST, 3
+                LIMIT $3
+                "#
+            )
+            .bind(from)
+            .bind(to)
+            .bind(limit + 1)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Failed to query tax liability: {}", e)))?;
+
+        let mut table = ReportTable::new(vec!["country", "region", "rate", "orders", "taxable_amount", "tax_amount"]);
+        for (country, region, rate, orders, taxable, tax) in rows {
+            table.push(vec![country.into(), region.into(), rate.into(), orders.into(), taxable.into(), tax.into()]);
+        }
+        Ok(limit_rows(table, limit))
+    }
+
+    async fn low_stock(&self, limit: i64) -> Result<ReportTable> {
+        let rows = self.reads.read(move |pool| async move {
+            sqlx::query_as::<_, (String, Option<String>, Option<String>, String, i32, i32, i32, i32)>(
+                r#"
+                SELECT p.title, v.title, COALESCE(v.sku, p.sku), loc.name,
+                       il.available_quantity, il.incoming_quantity, il.reorder_point, il.reorder_quantity
+                FROM inventory_levels il
+                JOIN products p ON p.id = il.product_id
+                LEFT JOIN product_variants v ON v.id = il.variant_id
+                JOIN inventory_locations loc ON loc.id = il.location_id
+                WHERE loc.is_active AND il.reorder_point > 0 AND il.available_quantity <= il.reorder_point
+                ORDER BY il.available_quantity - il.reorder_point, p.title
+                LIMIT $1
+                "#
+            )
+            .bind(limit + 1)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Failed to query low stock: {}", e)))?;
+
+        let mut table = ReportTable::new(vec![
+            "product", "variant", "sku", "location", "available", "incoming", "reorder_point", "reorder_quantity",
+        ]);
+        for (product, variant, sku, location, available, incoming, reorder_point, reorder_quantity) in rows {
+            table.push(vec![
+                product.into(),
+                variant.into(),
+                sku.into(),
+                location.into(),
+                available.into(),
+                incoming.into(),
+                reorder_point.into(),
+                reorder_quantity.into(),
+            ]);
+        }
+        Ok(limit_rows(table, limit))
+    }
+
+    async fn failed_payments(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<ReportTable> {
+        let rows = self.reads.read(move |pool| async move {
+            sqlx::query_as::<_, (DateTime<Utc>, String, String, Decimal, String, String, Option<String>)>(
+                r#"
+                SELECT pay.created_at, o.order_number, o.email, pay.amount, pay.currency::TEXT, pay.gateway,
+                       pay.error_message
+                FROM payments pay
+                JOIN orders o ON o.id = pay.order_id
+                WHERE pay.status = 'failed' AND pay.created_at >= $1 AND pay.created_at < $2
+                ORDER BY pay.created_at DESC
+                LIMIT $3
+                "#
+            )
+            .bind(from)
+            .bind(to)
+            .bind(limit + 1)
+            .fetch_all(&pool)
+            .await
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Failed to query failed payments: {}", e)))?;
+
+        let mut table = ReportTable::new(vec!["failed_at", "order", "email", "amount", "currency", "gateway", "error"]);
+        for (failed_at, order_number, email, amount, currency, gateway, error) in rows {
+            table.push(vec![
+                failed_at.format("%Y-%m-%d %H:%M:%S").to_string().into(),
+                order_number.into(),
+                email.into(),
+                amount.into(),
+                currency.into(),
+                gateway.into(),
+                error.into(),
+            ]);
+        }
+        Ok(limit_rows(table, limit))
+    }
+
+    async fn checkout_funnel(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<ReportTable> {
+        let sessions = PostgresAnalyticsRepository::new(self.db.clone())
+            .funnel_sessions(from, to)
+            .await?;
+        let percentage = |p: Option<f64>| p.and_then(|p| Decimal::try_from(p).ok()).map(|p| p.round_dp(1));
+
+        let mut table = ReportTable::new(vec!["step", "sessions", "pct_of_previous", "pct_of_first"]);
+        for step in FunnelReport::new(from, to, &sessions).steps {
+            table.push(vec![
+                step.event.as_str().into(),
+                step.sessions.into(),
+                percentage(step.conversion_from_previous).into(),
+                percentage(step.conversion_from_start).into(),
+            ]);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_rows() {
+        let mut table = ReportTable::new(vec!["n"]);
+        for n in 0..3 {
+            table.push(vec![ReportValue::Integer(n)]);
+        }
+        let kept = limit_rows(table.clone(), 3);
+        assert_eq!(kept.rows.len(), 3);
+        assert!(!kept.truncated);
+
+        let cut = limit_rows(table, 2);
+        assert_eq!(cut.rows.len(), 2);
+        assert!(cut.truncated);
+    }
+}
@@ -0,0 +1,417 @@
+//! Return (RMA) repository
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{
+        Address, CreateReturnRequest, CustomerAddress, NewReturnItem, ReturnAuthorization, ReturnEvent, ReturnItem,
+        ReturnOrder, ReturnStatus, ReturnableItem, CUSTOMER_ADDRESS_COLUMNS,
+    },
+    payment::agnostic::RefundResponse,
+};
+
+/// The captured payment a return is refunded to
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderPayment {
+    pub id: Uuid,
+    pub gateway: String,
+    pub gateway_payment_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+/// Repository trait for returns
+#[async_trait]
+pub trait ReturnRepository: Send + Sync {
+    /// The order a return is requested against, if it exists
+    async fn order(&self, order_id: Uuid) -> Result<Option<ReturnOrder>>;
+
+    /// An order's lines with the units already on returns
+    async fn returnable_items(&self, order_id: Uuid) -> Result<Vec<ReturnableItem>>;
+
+    /// Create a requested return with its lines
+    async fn create(
+        &self,
+        order: &ReturnOrder,
+        request: &CreateReturnRequest,
+        items: &[NewReturnItem],
+        actor_id: Option<Uuid>,
+    ) -> Result<ReturnAuthorization>;
+
+    /// Get a return
+    async fn find(&self, id: Uuid) -> Result<Option<ReturnAuthorization>>;
+
+    /// A return's lines
+    async fn items(&self, id: Uuid) -> Result<Vec<ReturnItem>>;
+
+    /// A return's status history, oldest first
+    async fn events(&self, id: Uuid) -> Result<Vec<ReturnEvent>>;
+
+    /// Returns, newest first, optionally by status and customer
+    async fn list(&self, status: Option<ReturnStatus>, customer_id: Option<Uuid>, limit: i64) -> Result<Vec<ReturnAuthorization>>;
+
+    /// Move a return from `from` to `to`; None if it was no longer in `from`
+    async fn transition(
+        &self,
+        id: Uuid,
+        from: ReturnStatus,
+        to: ReturnStatus,
+        actor_id: Option<Uuid>,
+        notes: Option<&str>,
+    ) -> Result<Option<ReturnAuthorization>>;
+
+    /// Record the return label
+    async fn set_label(
+        &self,
+        id: Uuid,
+        provider: &str,
+        service: &str,
+        tracking_number: Option<&str>,
+        label_url: Option<&str>,
+    ) -> Result<ReturnAuthorization>;
+
+    /// The order's captured payment
+    async fn order_payment(&self, order_id: Uuid) -> Result<Option<OrderPayment>>;
+
+    /// Record the gateway refund and mark a received return refunded
+    async fn record_refund(
+        &self,
+        rma: &ReturnAuthorization,
+        payment: &OrderPayment,
+        refund: &RefundResponse,
+        actor_id: Option<Uuid>,
+    ) -> Result<ReturnAuthorization>;
+
+    /// Where the customer ships from: the order's shipping address, else
+    /// the customer's default shipping address
+    async fn ship_from(&self, order_id: Uuid) -> Result<Option<Address>>;
+}
+
+/// PostgreSQL implementation of ReturnRepository
+pub struct PostgresReturnRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresReturnRepository {
+    /// Create a new PostgreSQL return repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ReturnRepository for PostgresReturnRepository {
+    async fn order(&self, order_id: Uuid) -> Result<Option<ReturnOrder>> {
+        sqlx::query_as::<_, ReturnOrder>(
+            r#"
+            SELECT o.id, o.order_number, o.customer_id, o.email,
+                   NULLIF(TRIM(CONCAT_WS(' ', c.first_name, c.last_name)), '') AS customer_name,
+                   o.currency::text AS currency, o.created_at
+            FROM orders o
+            LEFT JOIN customers c ON c.id = o.customer_id
+            WHERE o.id = $1
+            "#
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order: {}", e)))
+    }
+
+    async fn returnable_items(&self, order_id: Uuid) -> Result<Vec<ReturnableItem>> {
+        sqlx::query_as::<_, ReturnableItem>(
+            r#"
+            SELECT oi.id AS order_item_id, oi.title, oi.sku, oi.quantity,
+                   COALESCE((
+                       SELECT SUM(ri.quantity)::int
+                       FROM return_items ri
+                       JOIN returns r ON r.id = ri.return_id
+                       WHERE ri.order_item_id = oi.id AND r.status NOT IN ('rejected', 'cancelled')
+                   ), 0) AS returned_quantity,
+                   ROUND(oi.total / GREATEST(oi.quantity, 1), 2) AS unit_refund
+            FROM order_items oi
+            WHERE oi.order_id = $1
+            ORDER BY oi.created_at
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list returnable items: {}", e)))
+    }
+
+    async fn create(
+        &self,
+        order: &ReturnOrder,
+        request: &CreateReturnRequest,
+        items: &[NewReturnItem],
+        actor_id: Option<Uuid>,
+    ) -> Result<ReturnAuthorization> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let rma = sqlx::query_as::<_, ReturnAuthorization>(
+            r#"
+            INSERT INTO returns (order_id, customer_id, reason, customer_notes, currency, refund_amount)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(order.id)
+        .bind(order.customer_id)
+        .bind(&request.reason)
+        .bind(&request.customer_notes)
+        .bind(&order.currency)
+        .bind(NewReturnItem::total(items))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create return: {}", e)))?;
+
+        for item in items {
+            sqlx::query(
+                r#"
+                INSERT INTO return_items (return_id, order_item_id, quantity, unit_refund, reason)
+                VALUES ($1, $2, $3, $4, $5)
+                "#
+            )
+            .bind(rma.id)
+            .bind(item.order_item_id)
+            .bind(item.quantity)
+            .bind(item.unit_refund)
+            .bind(&item.reason)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to add return item: {}", e)))?;
+        }
+
+        sqlx::query("INSERT INTO return_events (return_id, status, actor_id, notes) VALUES ($1, 'requested', $2, $3)")
+            .bind(rma.id)
+            .bind(actor_id)
+            .bind(&request.customer_notes)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record return event: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit return: {}", e)))?;
+        Ok(rma)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<ReturnAuthorization>> {
+        sqlx::query_as::<_, ReturnAuthorization>("SELECT * FROM returns WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get return: {}", e)))
+    }
+
+    async fn items(&self, id: Uuid) -> Result<Vec<ReturnItem>> {
+        sqlx::query_as::<_, ReturnItem>(
+            r#"
+            SELECT ri.id, ri.return_id, ri.order_item_id, oi.title, oi.sku, ri.quantity, ri.unit_refund, ri.reason
+            FROM return_items ri
+            JOIN order_items oi ON oi.id = ri.order_item_id
+            WHERE ri.return_id = $1
+            ORDER BY oi.created_at
+            "#
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list return items: {}", e)))
+    }
+
+    async fn events(&self, id: Uuid) -> Result<Vec<ReturnEvent>> {
+        sqlx::query_as::<_, ReturnEvent>("SELECT * FROM return_events WHERE return_id = $1 ORDER BY created_at")
+            .bind(id)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list return events: {}", e)))
+    }
+
+    async fn list(&self, status: Option<ReturnStatus>, customer_id: Option<Uuid>, limit: i64) -> Result<Vec<ReturnAuthorization>> {
+        sqlx::query_as::<_, ReturnAuthorization>(
+            r#"
+            SELECT * FROM returns
+            WHERE ($1::return_status IS NULL OR status = $1)
+              AND ($2::uuid IS NULL OR customer_id = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(status)
+        .bind(customer_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list returns: {}", e)))
+    }
+
+    async fn transition(
+        &self,
+        id: Uuid,
+        from: ReturnStatus,
+        to: ReturnStatus,
+        actor_id: Option<Uuid>,
+        notes: Option<&str>,
+    ) -> Result<Option<ReturnAuthorization>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        // Approvals and rejections are decisions; receipt is timestamped
+        let rma = sqlx::query_as::<_, ReturnAuthorization>(
+            r#"
+            UPDATE returns
+            SET status = $3,
+                decided_by = CASE WHEN $3 IN ('approved', 'rejected') THEN $4 ELSE decided_by END,
+                decided_at = CASE WHEN $3 IN ('approved', 'rejected') THEN NOW() ELSE decided_at END,
+                decision_notes = CASE WHEN $3 IN ('approved', 'rejected') THEN $5 ELSE decision_notes END,
+                received_at = CASE WHEN $3 = 'received' THEN NOW() ELSE received_at END
+            WHERE id = $1 AND status = $2
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .bind(actor_id)
+        .bind(notes)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update return: {}", e)))?;
+
+        if rma.is_some() {
+            sqlx::query("INSERT INTO return_events (return_id, status, actor_id, notes) VALUES ($1, $2, $3, $4)")
+                .bind(id)
+                .bind(to)
+                .bind(actor_id)
+                .bind(notes)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to record return event: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit return: {}", e)))?;
+        Ok(rma)
+    }
+
+    async fn set_label(
+        &self,
+        id: Uuid,
+        provider: &str,
+        service: &str,
+        tracking_number: Option<&str>,
+        label_url: Option<&str>,
+    ) -> Result<ReturnAuthorization> {
+        sqlx::query_as::<_, ReturnAuthorization>(
+            r#"
+            UPDATE returns
+            SET label_provider = $2, label_service = $3, tracking_number = $4, label_url = $5
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(provider)
+        .bind(service)
+        .bind(tracking_number)
+        .bind(label_url)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save return label: {}", e)))
+    }
+
+    async fn order_payment(&self, order_id: Uuid) -> Result<Option<OrderPayment>> {
+        sqlx::query_as::<_, OrderPayment>(
+            r#"
+            SELECT id, gateway, gateway_payment_id, amount, currency::text AS currency
+            FROM payments
+            WHERE order_id = $1 AND status IN ('paid', 'refunded')
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order payment: {}", e)))
+    }
+
+    async fn record_refund(
+        &self,
+        rma: &ReturnAuthorization,
+        payment: &OrderPayment,
+        refund: &RefundResponse,
+        actor_id: Option<Uuid>,
+    ) -> Result<ReturnAuthorization> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let refund_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO refunds (payment_id, order_id, amount, currency, reason, status, gateway_refund_id)
+            VALUES ($1, $2, $3, $4::currency, $5, 'refunded', $6)
+            RETURNING id
+            "#
+        )
+        .bind(payment.id)
+        .bind(rma.order_id)
+        .bind(refund.amount)
+        .bind(&payment.currency)
+        .bind(format!("Return {}", rma.rma_number))
+        .bind(&refund.refund_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record refund: {}", e)))?;
+
+        let updated = sqlx::query_as::<_, ReturnAuthorization>(
+            r#"
+            UPDATE returns
+            SET status = 'refunded', refund_id = $2, gateway_refund_id = $3, refunded_at = NOW()
+            WHERE id = $1 AND status = 'received'
+            RETURNING *
+            "#
+        )
+        .bind(rma.id)
+        .bind(refund_id)
+        .bind(&refund.refund_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update return: {}", e)))?
+        .ok_or_else(|| Error::validation(format!("Return {} is no longer awaiting refund", rma.rma_number)))?;
+
+        sqlx::query("INSERT INTO return_events (return_id, status, actor_id, notes) VALUES ($1, 'refunded', $2, $3)")
+            .bind(rma.id)
+            .bind(actor_id)
+            .bind(format!("Refund {} of {} {}", refund.refund_id, refund.amount, payment.currency))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record return event: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit refund: {}", e)))?;
+        Ok(updated)
+    }
+
+    async fn ship_from(&self, order_id: Uuid) -> Result<Option<Address>> {
+        let address = sqlx::query_as::<_, CustomerAddress>(&format!(
+            r#"
+            SELECT {} FROM addresses
+            WHERE id = (SELECT shipping_address_id FROM orders WHERE id = $1)
+               OR (customer_id = (SELECT customer_id FROM orders WHERE id = $1) AND is_default_shipping)
+            ORDER BY id = (SELECT shipping_address_id FROM orders WHERE id = $1) DESC NULLS LAST
+            LIMIT 1
+            "#,
+            CUSTOMER_ADDRESS_COLUMNS
+        ))
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get return address: {}", e)))?;
+        Ok(address.map(|address| address.to_address()))
+    }
+}
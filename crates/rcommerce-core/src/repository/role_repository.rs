@@ -0,0 +1,176 @@
+//! Staff role repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{CreateRoleRequest, CustomerRole, Role, RoleAssignment, UpdateRoleRequest},
+};
+
+/// Repository trait for roles and role assignments
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    /// All roles by name
+    async fn list(&self) -> Result<Vec<Role>>;
+
+    /// Get a role
+    async fn find(&self, id: Uuid) -> Result<Option<Role>>;
+
+    /// Create a role
+    async fn create(&self, request: &CreateRoleRequest) -> Result<Role>;
+
+    /// Update a role; None if it does not exist
+    async fn update(&self, id: Uuid, request: &UpdateRoleRequest) -> Result<Option<Role>>;
+
+    /// Delete a role and its assignments
+    async fn delete(&self, id: Uuid) -> Result<bool>;
+
+    /// A customer's base role, if the customer exists
+    async fn customer_role(&self, customer_id: Uuid) -> Result<Option<CustomerRole>>;
+
+    /// Roles assigned to a customer
+    async fn assignments(&self, customer_id: Uuid) -> Result<Vec<RoleAssignment>>;
+
+    /// Assign a role (assigning it again is a no-op)
+    async fn assign(&self, customer_id: Uuid, role_id: Uuid, assigned_by: Option<Uuid>) -> Result<()>;
+
+    /// Remove a role from a customer
+    async fn unassign(&self, customer_id: Uuid, role_id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of RoleRepository
+pub struct PostgresRoleRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresRoleRepository {
+    /// Create a new PostgreSQL role repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for PostgresRoleRepository {
+    async fn list(&self) -> Result<Vec<Role>> {
+        sqlx::query_as::<_, Role>("SELECT * FROM roles ORDER BY name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list roles: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<Role>> {
+        sqlx::query_as::<_, Role>("SELECT * FROM roles WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get role: {}", e)))
+    }
+
+    async fn create(&self, request: &CreateRoleRequest) -> Result<Role> {
+        sqlx::query_as::<_, Role>(
+            r#"
+            INSERT INTO roles (name, description, permissions)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#
+        )
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(&request.permissions)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation(format!("A role named '{}' already exists", request.name))
+            }
+            e => Error::Other(format!("Failed to create role: {}", e)),
+        })
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateRoleRequest) -> Result<Option<Role>> {
+        sqlx::query_as::<_, Role>(
+            r#"
+            UPDATE roles
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                permissions = COALESCE($4, permissions)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(&request.permissions)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                Error::validation("A role with that name already exists")
+            }
+            e => Error::Other(format!("Failed to update role: {}", e)),
+        })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM roles WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete role: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn customer_role(&self, customer_id: Uuid) -> Result<Option<CustomerRole>> {
+        sqlx::query_scalar::<_, CustomerRole>("SELECT role FROM customers WHERE id = $1")
+            .bind(customer_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get customer role: {}", e)))
+    }
+
+    async fn assignments(&self, customer_id: Uuid) -> Result<Vec<RoleAssignment>> {
+        sqlx::query_as::<_, RoleAssignment>(
+            r#"
+            SELECT cr.customer_id, cr.role_id, r.name AS role_name, r.permissions, cr.assigned_by, cr.assigned_at
+            FROM customer_roles cr
+            JOIN roles r ON r.id = cr.role_id
+            WHERE cr.customer_id = $1
+            ORDER BY r.name
+            "#
+        )
+        .bind(customer_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list role assignments: {}", e)))
+    }
+
+    async fn assign(&self, customer_id: Uuid, role_id: Uuid, assigned_by: Option<Uuid>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO customer_roles (customer_id, role_id, assigned_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (customer_id, role_id) DO NOTHING
+            "#
+        )
+        .bind(customer_id)
+        .bind(role_id)
+        .bind(assigned_by)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to assign role: {}", e)))?;
+        Ok(())
+    }
+
+    async fn unassign(&self, customer_id: Uuid, role_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM customer_roles WHERE customer_id = $1 AND role_id = $2")
+            .bind(customer_id)
+            .bind(role_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to remove role: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
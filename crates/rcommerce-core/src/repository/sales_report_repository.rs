@@ -0,0 +1,158 @@
+//! Sales report repository
+//!
+//! Reads the daily sales rollups (materialized views of migration 067) and
+//! refreshes them.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::reports::sales::{DailySales, TopProduct, TopProductsBy};
+use crate::repository::Database;
+use crate::{Error, Result};
+
+/// Views refreshed by `refresh`, in order
+const SALES_VIEWS: [&str; 3] = ["sales_daily", "sales_product_daily", "storefront_sessions_daily"];
+
+/// Repository trait for the sales rollups
+#[async_trait]
+pub trait SalesReportRepository: Send + Sync {
+    /// Sales per day and channel between two days, both inclusive
+    async fn daily_sales(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailySales>>;
+
+    /// Best selling products between two days, both inclusive
+    async fn top_products(&self, from: NaiveDate, to: NaiveDate, by: TopProductsBy, limit: i64) -> Result<Vec<TopProduct>>;
+
+    /// Estimated storefront sessions between two days, both inclusive
+    async fn storefront_sessions(&self, from: NaiveDate, to: NaiveDate) -> Result<f64>;
+
+    /// When the views were last refreshed
+    async fn refreshed_at(&self) -> Result<Option<DateTime<Utc>>>;
+
+    /// Refresh every view without blocking reads; returns when
+    async fn refresh(&self) -> Result<DateTime<Utc>>;
+}
+
+/// PostgreSQL implementation of SalesReportRepository
+#[derive(Clone)]
+pub struct PostgresSalesReportRepository {
+    db: sqlx::PgPool,
+    /// Where report queries run; the primary unless read replicas are set
+    reads: Database,
+}
+
+impl PostgresSalesReportRepository {
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { reads: Database::new(db.clone()), db }
+    }
+
+    /// Run report queries on the read replicas of `db`
+    pub fn with_read_replicas(mut self, db: Database) -> Self {
+        self.reads = db;
+        self
+    }
+}
+
+#[async_trait]
+impl SalesReportRepository for PostgresSalesReportRepository {
+    async fn daily_sales(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailySales>> {
+        self.reads
+            .read(move |pool| async move {
+                sqlx::query_as::<_, DailySales>(
+                    r#"
+                    SELECT day, channel, orders, paid_orders, items_sold, gross_revenue,
+                           refunded_orders, refunded_amount
+                    FROM sales_daily
+                    WHERE day BETWEEN $1 AND $2
+                    ORDER BY day, channel
+                    "#,
+                )
+                .bind(from)
+                .bind(to)
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(|e| Error::Other(format!("Failed to query daily sales: {}", e)))
+    }
+
+    async fn top_products(&self, from: NaiveDate, to: NaiveDate, by: TopProductsBy, limit: i64) -> Result<Vec<TopProduct>> {
+        let order = match by {
+            TopProductsBy::Revenue => "revenue DESC, units DESC",
+            TopProductsBy::Units => "units DESC, revenue DESC",
+        };
+        let query = format!(
+            r#"
+            SELECT s.product_id, COALESCE(p.title, '') AS title,
+                   SUM(s.units)::BIGINT AS units, SUM(s.orders)::BIGINT AS orders,
+                   SUM(s.revenue) AS revenue
+            FROM sales_product_daily s
+            LEFT JOIN products p ON p.id = s.product_id
+            WHERE s.day BETWEEN $1 AND $2
+            GROUP BY s.product_id, p.title
+            ORDER BY {}, s.product_id
+            LIMIT $3
+            "#,
+            order
+        );
+        self.reads
+            .read(|pool| {
+                let query = query.clone();
+                async move {
+                    sqlx::query_as::<_, TopProduct>(&query)
+                        .bind(from)
+                        .bind(to)
+                        .bind(limit)
+                        .fetch_all(&pool)
+                        .await
+                }
+            })
+            .await
+            .map_err(|e| Error::Other(format!("Failed to query top products: {}", e)))
+    }
+
+    async fn storefront_sessions(&self, from: NaiveDate, to: NaiveDate) -> Result<f64> {
+        self.reads
+            .read(move |pool| async move {
+                sqlx::query_scalar::<_, f64>(
+                    "SELECT COALESCE(SUM(sessions), 0)::float8 FROM storefront_sessions_daily WHERE day BETWEEN $1 AND $2",
+                )
+                .bind(from)
+                .bind(to)
+                .fetch_one(&pool)
+                .await
+            })
+            .await
+            .map_err(|e| Error::Other(format!("Failed to query storefront sessions: {}", e)))
+    }
+
+    async fn refreshed_at(&self) -> Result<Option<DateTime<Utc>>> {
+        self.reads
+            .read(|pool| async move {
+                sqlx::query_scalar::<_, DateTime<Utc>>("SELECT refreshed_at FROM sales_view_refreshes")
+                    .fetch_optional(&pool)
+                    .await
+            })
+            .await
+            .map_err(|e| Error::Other(format!("Failed to query sales view refresh time: {}", e)))
+    }
+
+    async fn refresh(&self) -> Result<DateTime<Utc>> {
+        for view in SALES_VIEWS {
+            sqlx::query(&format!("REFRESH MATERIALIZED VIEW CONCURRENTLY {}", view))
+                .execute(&self.db)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to refresh {}: {}", view, e)))?;
+        }
+        sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            INSERT INTO sales_view_refreshes (id, refreshed_at)
+            VALUES (true, NOW())
+            ON CONFLICT (id) DO UPDATE SET refreshed_at = EXCLUDED.refreshed_at
+            RETURNING refreshed_at
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record sales view refresh: {}", e)))
+    }
+}
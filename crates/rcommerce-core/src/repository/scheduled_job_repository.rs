@@ -0,0 +1,283 @@
+//! Scheduled job repository
+//!
+//! Schedules, leases and run outcomes of recurring jobs, and the dead-letter
+//! queue of jobs that ran out of retries. Taking a lease is a single
+//! conditional UPDATE, so of the instances polling for a due job exactly one
+//! gets it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::models::{JobDeadLetter, JobFailure, ScheduledJob, DEAD_LETTER_DEAD, DEAD_LETTER_RETRIED};
+use crate::{Error, Result};
+
+/// Repository trait for scheduled jobs
+#[async_trait]
+pub trait ScheduledJobRepository: Send + Sync {
+    /// Every job, by name
+    async fn list(&self) -> Result<Vec<ScheduledJob>>;
+
+    async fn find(&self, name: &str) -> Result<Option<ScheduledJob>>;
+
+    /// Add a job with its default schedule; an existing job keeps its
+    /// schedule and paused flag and only gets the new description
+    async fn register(&self, name: &str, description: &str, schedule: &str, next_run_at: DateTime<Utc>) -> Result<ScheduledJob>;
+
+    /// Take the lease of a due (or requested) job whose lease is free;
+    /// None if it is not due or another instance holds it
+    async fn acquire(&self, name: &str, instance: &str, lease_secs: u64) -> Result<Option<ScheduledJob>>;
+
+    /// Record a run's outcome and the failures since the last success, and
+    /// release the lease; false if the lease was lost to another instance
+    #[allow(clippy::too_many_arguments)]
+    async fn finish(
+        &self,
+        name: &str,
+        instance: &str,
+        status: &str,
+        message: Option<&str>,
+        duration_ms: i64,
+        failures: &[JobFailure],
+        next_run_at: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    async fn set_paused(&self, name: &str, paused: bool) -> Result<Option<ScheduledJob>>;
+
+    /// Ask for a one-off run on the next poll
+    async fn request_run(&self, name: &str) -> Result<Option<ScheduledJob>>;
+
+    async fn set_schedule(&self, name: &str, schedule: &str, next_run_at: DateTime<Utc>) -> Result<Option<ScheduledJob>>;
+
+    /// Add a job that ran out of retries to the dead-letter queue
+    async fn dead_letter(&self, name: &str, failures: &[JobFailure]) -> Result<JobDeadLetter>;
+
+    /// Dead letters, newest first; all of them without a status
+    async fn dead_letters(&self, status: Option<&str>, limit: i64, offset: i64) -> Result<Vec<JobDeadLetter>>;
+
+    async fn find_dead_letter(&self, id: Uuid) -> Result<Option<JobDeadLetter>>;
+
+    /// Mark a `dead` entry retried (requesting a run of its job in the same
+    /// statement) or discarded; None if there is no such entry awaiting action
+    async fn resolve_dead_letter(&self, id: Uuid, status: &str) -> Result<Option<JobDeadLetter>>;
+}
+
+/// PostgreSQL implementation of ScheduledJobRepository
+#[derive(Clone)]
+pub struct PostgresScheduledJobRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresScheduledJobRepository {
+    /// Create a new PostgreSQL scheduled job repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ScheduledJobRepository for PostgresScheduledJobRepository {
+    async fn list(&self) -> Result<Vec<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>("SELECT * FROM scheduled_jobs ORDER BY name")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list scheduled jobs: {}", e)))
+    }
+
+    async fn find(&self, name: &str) -> Result<Option<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>("SELECT * FROM scheduled_jobs WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get scheduled job: {}", e)))
+    }
+
+    async fn register(&self, name: &str, description: &str, schedule: &str, next_run_at: DateTime<Utc>) -> Result<ScheduledJob> {
+        sqlx::query_as::<_, ScheduledJob>(
+            r#"
+            INSERT INTO scheduled_jobs (name, description, schedule, next_run_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (name) DO UPDATE
+                SET description = EXCLUDED.description, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(description)
+        .bind(schedule)
+        .bind(next_run_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to register scheduled job: {}", e)))
+    }
+
+    async fn acquire(&self, name: &str, instance: &str, lease_secs: u64) -> Result<Option<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>(
+            r#"
+            UPDATE scheduled_jobs
+            SET locked_by = $2,
+                locked_until = NOW() + make_interval(secs => $3),
+                last_started_at = NOW(),
+                run_requested = FALSE,
+                updated_at = NOW()
+            WHERE name = $1
+              AND (run_requested OR (NOT paused AND next_run_at <= NOW()))
+              AND (locked_until IS NULL OR locked_until < NOW())
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(instance)
+        .bind(lease_secs as f64)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to acquire scheduled job: {}", e)))
+    }
+
+    async fn finish(
+        &self,
+        name: &str,
+        instance: &str,
+        status: &str,
+        message: Option<&str>,
+        duration_ms: i64,
+        failures: &[JobFailure],
+        next_run_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE scheduled_jobs
+            SET locked_by = NULL,
+                locked_until = NULL,
+                last_finished_at = NOW(),
+                last_status = $3,
+                last_message = $4,
+                last_duration_ms = $5,
+                failed_attempts = $6,
+                failures = $7,
+                next_run_at = $8,
+                updated_at = NOW()
+            WHERE name = $1 AND locked_by = $2
+            "#,
+        )
+        .bind(name)
+        .bind(instance)
+        .bind(status)
+        .bind(message)
+        .bind(duration_ms)
+        .bind(failures.len() as i32)
+        .bind(Json(failures))
+        .bind(next_run_at)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record scheduled job run: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_paused(&self, name: &str, paused: bool) -> Result<Option<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>(
+            "UPDATE scheduled_jobs SET paused = $2, updated_at = NOW() WHERE name = $1 RETURNING *",
+        )
+        .bind(name)
+        .bind(paused)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to pause scheduled job: {}", e)))
+    }
+
+    async fn request_run(&self, name: &str) -> Result<Option<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>(
+            "UPDATE scheduled_jobs SET run_requested = TRUE, updated_at = NOW() WHERE name = $1 RETURNING *",
+        )
+        .bind(name)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to request scheduled job run: {}", e)))
+    }
+
+    async fn set_schedule(&self, name: &str, schedule: &str, next_run_at: DateTime<Utc>) -> Result<Option<ScheduledJob>> {
+        sqlx::query_as::<_, ScheduledJob>(
+            r#"
+            UPDATE scheduled_jobs
+            SET schedule = $2, next_run_at = $3, updated_at = NOW()
+            WHERE name = $1
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(schedule)
+        .bind(next_run_at)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update job schedule: {}", e)))
+    }
+
+    async fn dead_letter(&self, name: &str, failures: &[JobFailure]) -> Result<JobDeadLetter> {
+        let error = failures.last().map(|failure| failure.error.as_str()).unwrap_or_default();
+        sqlx::query_as::<_, JobDeadLetter>(
+            r#"
+            INSERT INTO job_dead_letters (job_name, attempts, error, failures)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(failures.len() as i32)
+        .bind(error)
+        .bind(Json(failures))
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to dead-letter job: {}", e)))
+    }
+
+    async fn dead_letters(&self, status: Option<&str>, limit: i64, offset: i64) -> Result<Vec<JobDeadLetter>> {
+        sqlx::query_as::<_, JobDeadLetter>(
+            r#"
+            SELECT * FROM job_dead_letters
+            WHERE $1::VARCHAR IS NULL OR status = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list dead-lettered jobs: {}", e)))
+    }
+
+    async fn find_dead_letter(&self, id: Uuid) -> Result<Option<JobDeadLetter>> {
+        sqlx::query_as::<_, JobDeadLetter>("SELECT * FROM job_dead_letters WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get dead-lettered job: {}", e)))
+    }
+
+    async fn resolve_dead_letter(&self, id: Uuid, status: &str) -> Result<Option<JobDeadLetter>> {
+        sqlx::query_as::<_, JobDeadLetter>(
+            r#"
+            WITH resolved AS (
+                UPDATE job_dead_letters
+                SET status = $2, resolved_at = NOW()
+                WHERE id = $1 AND status = $3
+                RETURNING *
+            ), requested AS (
+                UPDATE scheduled_jobs
+                SET run_requested = TRUE, updated_at = NOW()
+                WHERE $2 = $4 AND name IN (SELECT job_name FROM resolved)
+            )
+            SELECT * FROM resolved
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(DEAD_LETTER_DEAD)
+        .bind(DEAD_LETTER_RETRIED)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve dead-lettered job: {}", e)))
+    }
+}
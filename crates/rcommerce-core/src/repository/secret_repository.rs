@@ -0,0 +1,103 @@
+//! Stored secret repository
+//!
+//! Reading and replacing secrets kept in the database, so they can be
+//! sealed and rewrapped when the master key changes.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{Result, Error};
+
+/// Kind of stored secret, i.e. where it lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKind {
+    /// `webhooks.secret`, used to sign deliveries
+    WebhookSigningSecret,
+    /// `two_factor_credentials.secret`, the shared TOTP secret
+    TotpSecret,
+}
+
+impl SecretKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretKind::WebhookSigningSecret => "webhook signing secret",
+            SecretKind::TotpSecret => "two-factor secret",
+        }
+    }
+}
+
+/// A secret as stored
+#[derive(Debug, Clone)]
+pub struct StoredSecret {
+    pub kind: SecretKind,
+    pub id: Uuid,
+    pub value: String,
+}
+
+/// Repository trait for stored secrets
+#[async_trait]
+pub trait SecretRepository: Send + Sync {
+    /// Every stored secret
+    async fn list(&self) -> Result<Vec<StoredSecret>>;
+
+    /// Replace a secret's value if it has not changed since it was read;
+    /// false if it has
+    async fn replace(&self, secret: &StoredSecret, value: &str) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of SecretRepository
+pub struct PostgresSecretRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresSecretRepository {
+    /// Create a new PostgreSQL secret repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SecretRepository for PostgresSecretRepository {
+    async fn list(&self) -> Result<Vec<StoredSecret>> {
+        let rows = sqlx::query_as::<_, (Uuid, String)>("SELECT id, secret FROM webhooks ORDER BY created_at")
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list webhook secrets: {}", e)))?;
+
+        let totp_rows = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT customer_id, secret FROM two_factor_credentials ORDER BY created_at"
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list two-factor secrets: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, value)| StoredSecret { kind: SecretKind::WebhookSigningSecret, id, value })
+            .chain(
+                totp_rows
+                    .into_iter()
+                    .map(|(id, value)| StoredSecret { kind: SecretKind::TotpSecret, id, value }),
+            )
+            .collect())
+    }
+
+    async fn replace(&self, secret: &StoredSecret, value: &str) -> Result<bool> {
+        let result = match secret.kind {
+            SecretKind::WebhookSigningSecret => sqlx::query(
+                "UPDATE webhooks SET secret = $3, updated_at = NOW() WHERE id = $1 AND secret = $2"
+            ),
+            SecretKind::TotpSecret => sqlx::query(
+                "UPDATE two_factor_credentials SET secret = $3, updated_at = NOW() WHERE customer_id = $1 AND secret = $2"
+            ),
+        }
+        .bind(secret.id)
+        .bind(&secret.value)
+        .bind(value)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update {}: {}", secret.kind.as_str(), e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,632 @@
+//! Shipment repository: order lines left to ship, stock by location,
+//! fulfillments and backorders
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    common::Address,
+    models::{CustomerAddress, CUSTOMER_ADDRESS_COLUMNS},
+    order::{
+        Backorder, CustomsLine, FulfillmentPlan, LabelFormat, LocationStock, OpenOrderLine, PickListLabel, PlannedLine,
+        Shipment, ShipmentCustoms, ShippingOrder, ShippingPreference,
+    },
+};
+
+/// Repository trait for shipments and backorders
+#[async_trait]
+pub trait ShipmentRepository: Send + Sync {
+    /// An order with its and its customer's shipping preference
+    async fn find_order(&self, order_id: Uuid) -> Result<Option<ShippingOrder>>;
+
+    /// The order's shippable lines with units not in a (live) shipment
+    async fn open_lines(&self, order_id: Uuid) -> Result<Vec<OpenOrderLine>>;
+
+    /// Stock of the products at active locations, oldest location first,
+    /// counting the units the order's payment committed there
+    async fn stock(&self, order_id: Uuid, product_ids: &[Uuid]) -> Result<Vec<LocationStock>>;
+
+    /// The order's shipments with their items, oldest first
+    async fn shipments(&self, order_id: Uuid) -> Result<Vec<Shipment>>;
+
+    async fn backorders(&self, order_id: Uuid) -> Result<Vec<Backorder>>;
+
+    /// Orders with backorders, the longest waiting first
+    async fn backordered_orders(&self, limit: i64) -> Result<Vec<Uuid>>;
+
+    /// Create the plan's shipments, taking their units out of stock, and
+    /// make its backordered units the order's backorders, in one
+    /// transaction. Fails if a location no longer has the stock.
+    async fn apply_plan(&self, order: &ShippingOrder, plan: &FulfillmentPlan, is_backorder: bool) -> Result<Vec<Shipment>>;
+
+    /// A shipment with its items
+    async fn find_shipment(&self, shipment_id: Uuid) -> Result<Option<Shipment>>;
+
+    /// Code of the inventory location
+    async fn location_code(&self, location_id: Uuid) -> Result<Option<String>>;
+
+    /// Where the order ships to: its shipping address, else its
+    /// customer's default shipping address
+    async fn ship_to(&self, order_id: Uuid) -> Result<Option<Address>>;
+
+    /// The shipment's items as declared to customs, with the order's incoterm
+    async fn customs(&self, shipment_id: Uuid) -> Result<ShipmentCustoms>;
+
+    /// Record the label bought for a shipment
+    async fn set_label(
+        &self,
+        shipment_id: Uuid,
+        carrier: &str,
+        service_code: &str,
+        tracking_number: Option<&str>,
+        label_url: Option<&str>,
+    ) -> Result<Shipment>;
+
+    /// Record where a shipment's label file is stored
+    async fn set_label_file(&self, shipment_id: Uuid, label_key: &str, format: LabelFormat) -> Result<Shipment>;
+
+    /// The orders' live shipments with a stored label, in the order of the
+    /// IDs; an order without one has a single row with no label
+    async fn pick_list_labels(&self, order_ids: &[Uuid]) -> Result<Vec<PickListLabel>>;
+
+    /// None clears it; false if there is no such order
+    async fn set_order_preference(&self, order_id: Uuid, preference: Option<ShippingPreference>) -> Result<bool>;
+
+    /// None clears it; false if there is no such customer
+    async fn set_customer_preference(&self, customer_id: Uuid, preference: Option<ShippingPreference>) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of ShipmentRepository
+pub struct PostgresShipmentRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresShipmentRepository {
+    /// Create a new PostgreSQL shipment repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+const SHIPMENT_COLUMNS: &str = "id, order_id, location_id, status, is_backorder, tracking_company, tracking_number, \
+    service_code, label_url, label_key, label_format, created_at";
+
+/// Shippable lines of order $1 with units not in a live shipment
+const OPEN_LINES_SQL: &str = r#"
+    SELECT oi.id AS order_item_id, oi.product_id, oi.variant_id, oi.title,
+           (oi.quantity - COALESCE(SUM(fi.quantity), 0))::INT4 AS quantity
+    FROM order_items oi
+    LEFT JOIN fulfillment_items fi ON fi.order_item_id = oi.id
+        AND EXISTS (
+            SELECT 1 FROM fulfillments f
+            WHERE f.id = fi.fulfillment_id AND f.status NOT IN ('cancelled', 'returned')
+        )
+    WHERE oi.order_id = $1
+      AND oi.requires_shipping
+      AND oi.product_id IS NOT NULL
+      AND NOT COALESCE(oi.is_digital, false)
+    GROUP BY oi.id
+    HAVING oi.quantity - COALESCE(SUM(fi.quantity), 0) > 0
+    ORDER BY oi.created_at, oi.id
+"#;
+
+/// Where an order ships to: its shipping address, else its customer's
+/// default shipping address
+pub(crate) async fn order_ship_to(db: &sqlx::PgPool, order_id: Uuid) -> Result<Option<Address>> {
+    let address = sqlx::query_as::<_, CustomerAddress>(&format!(
+        r#"
+        SELECT {} FROM addresses
+        WHERE id = (SELECT shipping_address_id FROM orders WHERE id = $1)
+           OR (customer_id = (SELECT customer_id FROM orders WHERE id = $1) AND is_default_shipping)
+        ORDER BY id = (SELECT shipping_address_id FROM orders WHERE id = $1) DESC NULLS LAST
+        LIMIT 1
+        "#,
+        CUSTOMER_ADDRESS_COLUMNS
+    ))
+    .bind(order_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to get shipping address: {}", e)))?;
+    Ok(address.map(|address| address.to_address()))
+}
+
+#[async_trait]
+impl ShipmentRepository for PostgresShipmentRepository {
+    async fn find_order(&self, order_id: Uuid) -> Result<Option<ShippingOrder>> {
+        sqlx::query_as::<_, ShippingOrder>(
+            r#"
+            SELECT o.id, o.order_number, o.email, o.customer_id, o.status::TEXT AS status,
+                   o.shipping_preference AS order_preference,
+                   c.shipping_preference AS customer_preference,
+                   COALESCE(c.email_notifications, true) AS email_notifications,
+                   o.pickup_location_id
+            FROM orders o
+            LEFT JOIN customers c ON c.id = o.customer_id
+            WHERE o.id = $1
+            "#
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order: {}", e)))
+    }
+
+    async fn open_lines(&self, order_id: Uuid) -> Result<Vec<OpenOrderLine>> {
+        sqlx::query_as::<_, OpenOrderLine>(OPEN_LINES_SQL)
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get order lines to ship: {}", e)))
+    }
+
+    async fn stock(&self, order_id: Uuid, product_ids: &[Uuid]) -> Result<Vec<LocationStock>> {
+        sqlx::query_as::<_, LocationStock>(
+            r#"
+            SELECT il.location_id, il.product_id, il.variant_id, il.available_quantity + c.quantity AS available
+            FROM inventory_levels il
+            JOIN inventory_locations l ON l.id = il.location_id
+            CROSS JOIN LATERAL (
+                SELECT COALESCE(SUM(r.quantity), 0)::INTEGER AS quantity
+                FROM stock_reservations r
+                WHERE r.order_id = $2 AND r.status = 'committed'
+                  AND r.product_id = il.product_id
+                  AND r.variant_id IS NOT DISTINCT FROM il.variant_id
+                  AND r.location_id = il.location_id
+            ) c
+            WHERE l.is_active AND il.product_id = ANY($1) AND il.available_quantity + c.quantity > 0
+            ORDER BY l.created_at, l.id
+            "#
+        )
+        .bind(product_ids)
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get stock by location: {}", e)))
+    }
+
+    async fn shipments(&self, order_id: Uuid) -> Result<Vec<Shipment>> {
+        let mut shipments = sqlx::query_as::<_, Shipment>(&format!(
+            "SELECT {} FROM fulfillments WHERE order_id = $1 ORDER BY created_at, id",
+            SHIPMENT_COLUMNS
+        ))
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list shipments: {}", e)))?;
+
+        let items = sqlx::query_as::<_, (Uuid, Uuid, i32)>(
+            r#"
+            SELECT fi.fulfillment_id, fi.order_item_id, fi.quantity
+            FROM fulfillment_items fi
+            JOIN fulfillments f ON f.id = fi.fulfillment_id
+            WHERE f.order_id = $1
+            ORDER BY fi.created_at, fi.id
+            "#
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list shipment items: {}", e)))?;
+
+        for (fulfillment_id, order_item_id, quantity) in items {
+            if let Some(shipment) = shipments.iter_mut().find(|shipment| shipment.id == fulfillment_id) {
+                shipment.items.push(PlannedLine { order_item_id, quantity });
+            }
+        }
+        Ok(shipments)
+    }
+
+    async fn backorders(&self, order_id: Uuid) -> Result<Vec<Backorder>> {
+        sqlx::query_as::<_, Backorder>(
+            "SELECT * FROM order_backorders WHERE order_id = $1 ORDER BY created_at, id"
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list backorders: {}", e)))
+    }
+
+    async fn backordered_orders(&self, limit: i64) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT b.order_id
+            FROM order_backorders b
+            JOIN orders o ON o.id = b.order_id
+            WHERE o.status IN ('confirmed', 'processing')
+            GROUP BY b.order_id
+            ORDER BY MIN(b.created_at), b.order_id
+            LIMIT $1
+            "#
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list backordered orders: {}", e)))
+    }
+
+    async fn apply_plan(&self, order: &ShippingOrder, plan: &FulfillmentPlan, is_backorder: bool) -> Result<Vec<Shipment>> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+        let reference = format!("order:{}", order.order_number);
+        let changed = || Error::validation(format!("Order {} changed while shipping it; try again", order.order_number));
+
+        // One plan at a time per order, and only for units still open
+        sqlx::query("SELECT 1 FROM orders WHERE id = $1 FOR UPDATE")
+            .bind(order.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to lock order: {}", e)))?;
+        let open = sqlx::query_as::<_, OpenOrderLine>(OPEN_LINES_SQL)
+            .bind(order.id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get order lines to ship: {}", e)))?;
+        for line in &open {
+            let planned: i32 = plan
+                .shipments
+                .iter()
+                .flat_map(|shipment| &shipment.lines)
+                .chain(&plan.backordered)
+                .filter(|planned| planned.order_item_id == line.order_item_id)
+                .map(|planned| planned.quantity)
+                .sum();
+            if planned != line.quantity {
+                return Err(changed());
+            }
+        }
+        let mut planned_lines = plan.shipments.iter().flat_map(|shipment| &shipment.lines).chain(&plan.backordered);
+        if planned_lines.any(|planned| !open.iter().any(|line| line.order_item_id == planned.order_item_id)) {
+            return Err(changed());
+        }
+
+        let mut created = Vec::new();
+        for planned in &plan.shipments {
+            let mut shipment = sqlx::query_as::<_, Shipment>(&format!(
+                r#"
+                INSERT INTO fulfillments (order_id, status, location_id, is_backorder)
+                VALUES ($1, 'pending', $2, $3)
+                RETURNING {}
+                "#,
+                SHIPMENT_COLUMNS
+            ))
+            .bind(order.id)
+            .bind(planned.location_id)
+            .bind(is_backorder)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to create shipment: {}", e)))?;
+
+            for line in &planned.lines {
+                sqlx::query(
+                    "INSERT INTO fulfillment_items (fulfillment_id, order_item_id, quantity) VALUES ($1, $2, $3)"
+                )
+                .bind(shipment.id)
+                .bind(line.order_item_id)
+                .bind(line.quantity)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to add shipment item: {}", e)))?;
+
+                // Units the order's payment committed here ship first
+                let committed = sqlx::query_as::<_, (Uuid, i32)>(
+                    r#"
+                    SELECT r.id, r.quantity
+                    FROM stock_reservations r
+                    JOIN order_items oi ON oi.id = $1
+                    WHERE r.order_id = oi.order_id AND r.status = 'committed'
+                      AND r.product_id = oi.product_id
+                      AND r.variant_id IS NOT DISTINCT FROM oi.variant_id
+                      AND r.location_id = $2
+                    ORDER BY r.created_at, r.id
+                    FOR UPDATE OF r
+                    "#
+                )
+                .bind(line.order_item_id)
+                .bind(planned.location_id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to get committed stock: {}", e)))?;
+                let (from_committed, left) = take_committed(&committed, line.quantity);
+                for (reservation_id, quantity) in left {
+                    sqlx::query(
+                        r#"
+                        UPDATE stock_reservations
+                        SET quantity = CASE WHEN $2 > 0 THEN $2 ELSE quantity END,
+                            status = CASE WHEN $2 > 0 THEN status ELSE 'released' END
+                        WHERE id = $1
+                        "#
+                    )
+                    .bind(reservation_id)
+                    .bind(quantity)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| Error::Other(format!("Failed to take committed stock: {}", e)))?;
+                }
+
+                let taken = sqlx::query_as::<_, (Uuid, Option<Uuid>)>(
+                    r#"
+                    UPDATE inventory_levels il
+                    SET available_quantity = il.available_quantity - ($3 - $4),
+                        reserved_quantity = il.reserved_quantity - $4,
+                        updated_at = NOW()
+                    FROM order_items oi
+                    WHERE oi.id = $1
+                      AND il.product_id = oi.product_id
+                      AND il.variant_id IS NOT DISTINCT FROM oi.variant_id
+                      AND il.location_id = $2
+                      AND il.available_quantity >= $3 - $4
+                    RETURNING il.product_id, il.variant_id
+                    "#
+                )
+                .bind(line.order_item_id)
+                .bind(planned.location_id)
+                .bind(line.quantity)
+                .bind(from_committed)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to take shipment stock: {}", e)))?;
+                let Some((product_id, variant_id)) = taken else {
+                    return Err(changed());
+                };
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO stock_movements (product_id, variant_id, location_id, quantity, movement_type, reference, notes)
+                    VALUES ($1, $2, $3, $4, 'out', $5, $6)
+                    "#
+                )
+                .bind(product_id)
+                .bind(variant_id)
+                .bind(planned.location_id)
+                .bind(-line.quantity)
+                .bind(&reference)
+                .bind(format!("shipment:{}", shipment.id))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to record stock movement: {}", e)))?;
+
+                shipment.items.push(line.clone());
+            }
+            created.push(shipment);
+        }
+
+        // What is left waiting replaces the order's backorders
+        sqlx::query("DELETE FROM order_backorders WHERE order_id = $1 AND NOT (order_item_id = ANY($2))")
+            .bind(order.id)
+            .bind(plan.backordered.iter().map(|line| line.order_item_id).collect::<Vec<_>>())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to clear backorders: {}", e)))?;
+        for line in &plan.backordered {
+            sqlx::query(
+                r#"
+                INSERT INTO order_backorders (order_id, order_item_id, product_id, variant_id, quantity)
+                SELECT oi.order_id, oi.id, oi.product_id, oi.variant_id, $2
+                FROM order_items oi WHERE oi.id = $1
+                ON CONFLICT (order_item_id) DO UPDATE
+                SET quantity = EXCLUDED.quantity, updated_at = NOW()
+                "#
+            )
+            .bind(line.order_item_id)
+            .bind(line.quantity)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to record backorder: {}", e)))?;
+        }
+
+        // Partly shipped until nothing is backordered
+        let shipped_before = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM fulfillments WHERE order_id = $1 AND status NOT IN ('cancelled', 'returned'))"
+        )
+        .bind(order.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to check shipments: {}", e)))?;
+        let status = match (shipped_before, plan.backordered.is_empty()) {
+            (true, false) => Some("partial"),
+            (true, true) => Some("processing"),
+            (false, _) => None,
+        };
+        if let Some(status) = status {
+            sqlx::query(
+                "UPDATE orders SET fulfillment_status = $2::fulfillment_status, updated_at = NOW() WHERE id = $1"
+            )
+            .bind(order.id)
+            .bind(status)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update order fulfillment status: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit shipments: {}", e)))?;
+        Ok(created)
+    }
+
+    async fn find_shipment(&self, shipment_id: Uuid) -> Result<Option<Shipment>> {
+        let shipment = sqlx::query_as::<_, Shipment>(&format!(
+            "SELECT {} FROM fulfillments WHERE id = $1",
+            SHIPMENT_COLUMNS
+        ))
+        .bind(shipment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipment: {}", e)))?;
+        let Some(mut shipment) = shipment else {
+            return Ok(None);
+        };
+
+        shipment.items = sqlx::query_as::<_, (Uuid, i32)>(
+            "SELECT order_item_id, quantity FROM fulfillment_items WHERE fulfillment_id = $1 ORDER BY created_at, id"
+        )
+        .bind(shipment_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list shipment items: {}", e)))?
+        .into_iter()
+        .map(|(order_item_id, quantity)| PlannedLine { order_item_id, quantity })
+        .collect();
+        Ok(Some(shipment))
+    }
+
+    async fn location_code(&self, location_id: Uuid) -> Result<Option<String>> {
+        sqlx::query_scalar::<_, String>("SELECT code FROM inventory_locations WHERE id = $1")
+            .bind(location_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get location: {}", e)))
+    }
+
+    async fn ship_to(&self, order_id: Uuid) -> Result<Option<Address>> {
+        order_ship_to(&self.db, order_id).await
+    }
+
+    async fn customs(&self, shipment_id: Uuid) -> Result<ShipmentCustoms> {
+        let (incoterm, currency) = sqlx::query_as::<_, (Option<crate::tax::Incoterm>, String)>(
+            "SELECT o.incoterm, o.currency FROM fulfillments f JOIN orders o ON o.id = f.order_id WHERE f.id = $1"
+        )
+        .bind(shipment_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipment order: {}", e)))?
+        .ok_or_else(|| Error::not_found("Shipment not found"))?;
+
+        let lines = sqlx::query_as::<_, CustomsLine>(
+            r#"
+            SELECT COALESCE(pc.description, oi.title) AS description, fi.quantity,
+                   oi.price * fi.quantity AS value, oi.weight * fi.quantity AS weight,
+                   oi.weight_unit::TEXT AS weight_unit, pc.hs_code, pc.origin_country
+            FROM fulfillment_items fi
+            JOIN order_items oi ON oi.id = fi.order_item_id
+            LEFT JOIN product_customs pc ON pc.product_id = oi.product_id
+            WHERE fi.fulfillment_id = $1
+            ORDER BY fi.created_at, fi.id
+            "#
+        )
+        .bind(shipment_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get shipment customs lines: {}", e)))?;
+
+        Ok(ShipmentCustoms { incoterm, currency, lines })
+    }
+
+    async fn set_label(
+        &self,
+        shipment_id: Uuid,
+        carrier: &str,
+        service_code: &str,
+        tracking_number: Option<&str>,
+        label_url: Option<&str>,
+    ) -> Result<Shipment> {
+        sqlx::query(
+            r#"
+            UPDATE fulfillments
+            SET tracking_company = $2, service_code = $3, tracking_number = $4, label_url = $5, updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(shipment_id)
+        .bind(carrier)
+        .bind(service_code)
+        .bind(tracking_number)
+        .bind(label_url)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save shipment label: {}", e)))?;
+
+        self.find_shipment(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))
+    }
+
+    async fn set_label_file(&self, shipment_id: Uuid, label_key: &str, format: LabelFormat) -> Result<Shipment> {
+        sqlx::query(
+            r#"
+            UPDATE fulfillments
+            SET label_key = $2, label_format = $3, label_stored_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(shipment_id)
+        .bind(label_key)
+        .bind(format)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save shipment label file: {}", e)))?;
+
+        self.find_shipment(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))
+    }
+
+    async fn pick_list_labels(&self, order_ids: &[Uuid]) -> Result<Vec<PickListLabel>> {
+        sqlx::query_as::<_, PickListLabel>(
+            r#"
+            SELECT o.id AS order_id, o.order_number, f.id AS shipment_id, f.label_key, f.label_format
+            FROM orders o
+            LEFT JOIN fulfillments f ON f.order_id = o.id
+                AND f.label_key IS NOT NULL
+                AND f.status NOT IN ('cancelled', 'returned')
+            WHERE o.id = ANY($1)
+            ORDER BY array_position($1, o.id), f.created_at, f.id
+            "#
+        )
+        .bind(order_ids)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to load pick list labels: {}", e)))
+    }
+
+    async fn set_order_preference(&self, order_id: Uuid, preference: Option<ShippingPreference>) -> Result<bool> {
+        let result = sqlx::query("UPDATE orders SET shipping_preference = $2, updated_at = NOW() WHERE id = $1")
+            .bind(order_id)
+            .bind(preference)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to set order shipping preference: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_customer_preference(&self, customer_id: Uuid, preference: Option<ShippingPreference>) -> Result<bool> {
+        let result = sqlx::query("UPDATE customers SET shipping_preference = $2, updated_at = NOW() WHERE id = $1")
+            .bind(customer_id)
+            .bind(preference)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to set customer shipping preference: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Take up to `quantity` units from the order's committed reservations,
+/// oldest first: the units taken and what is left of each reservation
+/// touched, 0 once it is used up
+fn take_committed(reservations: &[(Uuid, i32)], quantity: i32) -> (i32, Vec<(Uuid, i32)>) {
+    let mut taken = 0;
+    let mut left = Vec::new();
+    for (id, reserved) in reservations {
+        if taken == quantity {
+            break;
+        }
+        let take = (*reserved).min(quantity - taken);
+        taken += take;
+        left.push((*id, reserved - take));
+    }
+    (taken, left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_committed() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(take_committed(&[(a, 2), (b, 3)], 4), (4, vec![(a, 0), (b, 1)]));
+        assert_eq!(take_committed(&[(a, 2), (b, 3)], 2), (2, vec![(a, 0)]));
+        assert_eq!(take_committed(&[(a, 2)], 5), (2, vec![(a, 0)]));
+        assert_eq!(take_committed(&[], 3), (0, vec![]));
+    }
+}
@@ -0,0 +1,114 @@
+//! Soft delete repository
+//!
+//! Marking products, customers and orders deleted, restoring them, and
+//! removing them for good once past retention.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{DeletedRecord, SoftDeleteKind},
+};
+
+/// Repository trait for soft deletes
+#[async_trait]
+pub trait SoftDeleteRepository: Send + Sync {
+    /// Mark a record deleted; false if it doesn't exist or is already deleted
+    async fn soft_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool>;
+
+    /// Clear the deleted mark; false if the record isn't deleted
+    async fn restore(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool>;
+
+    /// Deleted records, most recently deleted first
+    async fn list_deleted(&self, kind: SoftDeleteKind, limit: i64) -> Result<Vec<DeletedRecord>>;
+
+    /// IDs of records deleted before a time, oldest first
+    async fn expired(&self, kind: SoftDeleteKind, before: DateTime<Utc>, limit: i64) -> Result<Vec<Uuid>>;
+
+    /// Remove a deleted record for good; errors if it is still referenced
+    async fn hard_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool>;
+}
+
+/// PostgreSQL implementation of SoftDeleteRepository
+pub struct PostgresSoftDeleteRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresSoftDeleteRepository {
+    /// Create a new PostgreSQL soft delete repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SoftDeleteRepository for PostgresSoftDeleteRepository {
+    async fn soft_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+        let query = format!(
+            "UPDATE {} SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+            kind.table()
+        );
+        let result = sqlx::query(&query)
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete {}: {}", kind, e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+        let query = format!(
+            "UPDATE {} SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND deleted_at IS NOT NULL",
+            kind.table()
+        );
+        let result = sqlx::query(&query)
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to restore {}: {}", kind, e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_deleted(&self, kind: SoftDeleteKind, limit: i64) -> Result<Vec<DeletedRecord>> {
+        let query = format!(
+            r#"
+            SELECT id, {}::text AS label, deleted_at FROM {}
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            LIMIT $1
+            "#,
+            kind.label_column(),
+            kind.table()
+        );
+        sqlx::query_as::<_, DeletedRecord>(&query)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list deleted {}: {}", kind, e)))
+    }
+
+    async fn expired(&self, kind: SoftDeleteKind, before: DateTime<Utc>, limit: i64) -> Result<Vec<Uuid>> {
+        let query = format!(
+            "SELECT id FROM {} WHERE deleted_at < $1 ORDER BY deleted_at LIMIT $2",
+            kind.table()
+        );
+        sqlx::query_scalar::<_, Uuid>(&query)
+            .bind(before)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list deleted {}: {}", kind, e)))
+    }
+
+    async fn hard_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+        let query = format!("DELETE FROM {} WHERE id = $1 AND deleted_at IS NOT NULL", kind.table());
+        let result = sqlx::query(&query)
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge {} {}: {}", kind, id, e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -228,7 +228,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             r#"
             SELECT 
                 DATE_TRUNC('{}', created_at) as period_start,
-                COALESCE(SUM(total), 0) as total_revenue,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as total_revenue,
                 COUNT(*) as total_orders,
                 COALESCE(SUM((SELECT SUM(quantity) FROM order_items WHERE order_id = orders.id)), 0) as total_items_sold
             FROM orders
@@ -308,7 +308,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             r#"
             SELECT 
                 COUNT(*) as total_orders,
-                COALESCE(SUM(total), 0) as total_revenue
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as total_revenue
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             "#
@@ -336,7 +336,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             SELECT 
                 status::text as status,
                 COUNT(*) as count,
-                COALESCE(SUM(total), 0) as revenue
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             GROUP BY status
@@ -364,7 +364,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             SELECT 
                 payment_status::text as status,
                 COUNT(*) as count,
-                COALESCE(SUM(total), 0) as revenue
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             GROUP BY payment_status
@@ -392,7 +392,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             SELECT 
                 fulfillment_status::text as status,
                 COUNT(*) as count,
-                COALESCE(SUM(total), 0) as revenue
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             GROUP BY fulfillment_status
@@ -437,7 +437,7 @@ impl StatisticsRepository for PgStatisticsRepository {
                 p.title as product_name,
                 p.sku,
                 COALESCE(SUM(oi.quantity), 0) as units_sold,
-                COALESCE(SUM(oi.total), 0) as revenue,
+                COALESCE(ROUND(SUM(oi.total * COALESCE(o.exchange_rate, 1)), 2), 0) as revenue,
                 COUNT(DISTINCT oi.order_id) as orders_count
             FROM products p
             LEFT JOIN order_items oi ON p.id = oi.product_id
@@ -539,7 +539,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             r#"
             SELECT COALESCE(AVG(customer_total), 0)
             FROM (
-                SELECT customer_id, SUM(total) as customer_total
+                SELECT customer_id, ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2) as customer_total
                 FROM orders
                 WHERE customer_id IS NOT NULL
                 AND status NOT IN ('cancelled', 'refunded')
@@ -566,7 +566,7 @@ impl StatisticsRepository for PgStatisticsRepository {
         let totals = sqlx::query(
             r#"
             SELECT 
-                COALESCE(SUM(total), 0) as total_revenue,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as total_revenue,
                 COUNT(*) as total_orders
             FROM orders
             WHERE status NOT IN ('cancelled', 'refunded')
@@ -611,7 +611,7 @@ impl StatisticsRepository for PgStatisticsRepository {
         let today_metrics = sqlx::query(
             r#"
             SELECT 
-                COALESCE(SUM(total), 0) as revenue_today,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue_today,
                 COUNT(*) as orders_today
             FROM orders
             WHERE created_at >= $1
@@ -635,7 +635,7 @@ impl StatisticsRepository for PgStatisticsRepository {
         let month_metrics = sqlx::query(
             r#"
             SELECT 
-                COALESCE(SUM(total), 0) as revenue_this_month,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue_this_month,
                 COUNT(*) as orders_this_month
             FROM orders
             WHERE created_at >= $1
@@ -676,7 +676,7 @@ impl StatisticsRepository for PgStatisticsRepository {
             r#"
             SELECT 
                 DATE_TRUNC('{}', created_at) as period,
-                COALESCE(SUM(total), 0) as revenue,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue,
                 COUNT(*) as orders
             FROM orders
             WHERE created_at >= DATE_TRUNC('{}', NOW() - INTERVAL '{} {}')
@@ -730,10 +730,10 @@ impl StatisticsRepository for PgStatisticsRepository {
         let current = sqlx::query(
             r#"
             SELECT 
-                COALESCE(SUM(total), 0) as revenue,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue,
                 COUNT(*) as orders,
                 COUNT(DISTINCT customer_id) as customers,
-                COALESCE(AVG(total), 0) as aov
+                COALESCE(ROUND(AVG(total * COALESCE(exchange_rate, 1)), 2), 0) as aov
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             AND status NOT IN ('cancelled', 'refunded')
@@ -758,10 +758,10 @@ impl StatisticsRepository for PgStatisticsRepository {
         let previous = sqlx::query(
             r#"
             SELECT 
-                COALESCE(SUM(total), 0) as revenue,
+                COALESCE(ROUND(SUM(total * COALESCE(exchange_rate, 1)), 2), 0) as revenue,
                 COUNT(*) as orders,
                 COUNT(DISTINCT customer_id) as customers,
-                COALESCE(AVG(total), 0) as aov
+                COALESCE(ROUND(AVG(total * COALESCE(exchange_rate, 1)), 2), 0) as aov
             FROM orders
             WHERE created_at >= $1 AND created_at <= $2
             AND status NOT IN ('cancelled', 'refunded')
@@ -0,0 +1,338 @@
+//! Stock adjustment repository
+//!
+//! Posting an adjustment locks the stock level, applies the change, records
+//! an `adjustment` stock movement and closes the request in one transaction.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    inventory::approval::{
+        CreateStockAdjustmentRequest, StockAdjustmentAuditEntry, StockAdjustmentRequest, StockAdjustmentStatus,
+    },
+    models::CustomerRole,
+};
+
+/// Repository trait for stock adjustments
+#[async_trait]
+pub trait StockAdjustmentRepository: Send + Sync {
+    /// Role of a staff member or customer
+    async fn actor_role(&self, actor_id: Uuid) -> Result<Option<CustomerRole>>;
+
+    /// IDs and emails of users with any of the roles
+    async fn find_approvers(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>>;
+
+    /// Available quantity at a location; None if the product is not stocked there
+    async fn current_quantity(&self, product_id: Uuid, variant_id: Option<Uuid>, location_id: Uuid) -> Result<Option<i32>>;
+
+    /// Create a pending adjustment
+    async fn create(
+        &self,
+        request: &CreateStockAdjustmentRequest,
+        requested_by: Uuid,
+        quantity_at_request: i32,
+        requires_approval: bool,
+    ) -> Result<StockAdjustmentRequest>;
+
+    /// Get an adjustment
+    async fn find(&self, id: Uuid) -> Result<Option<StockAdjustmentRequest>>;
+
+    /// List adjustments, newest first
+    async fn list(&self, status: Option<StockAdjustmentStatus>, limit: i64) -> Result<Vec<StockAdjustmentRequest>>;
+
+    /// An adjustment's audit trail, oldest first
+    async fn audit_trail(&self, id: Uuid) -> Result<Vec<StockAdjustmentAuditEntry>>;
+
+    /// Approve a pending adjustment and post it as a stock movement.
+    /// `approved_by` is None for adjustments below the thresholds.
+    async fn post(&self, id: Uuid, approved_by: Option<Uuid>, notes: Option<&str>) -> Result<StockAdjustmentRequest>;
+
+    /// Reject or cancel a pending adjustment. Returns None if it is no longer pending.
+    async fn close(
+        &self,
+        id: Uuid,
+        status: StockAdjustmentStatus,
+        actor_id: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<StockAdjustmentRequest>>;
+}
+
+/// PostgreSQL implementation of StockAdjustmentRepository
+pub struct PostgresStockAdjustmentRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresStockAdjustmentRepository {
+    /// Create a new PostgreSQL stock adjustment repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+async fn record_audit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    adjustment_id: Uuid,
+    action: &str,
+    actor_id: Option<Uuid>,
+    notes: Option<&str>,
+) -> Result<()> {
+    sqlx::query("INSERT INTO stock_adjustment_audit (adjustment_id, action, actor_id, notes) VALUES ($1, $2, $3, $4)")
+        .bind(adjustment_id)
+        .bind(action)
+        .bind(actor_id)
+        .bind(notes)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record stock adjustment audit: {}", e)))?;
+    Ok(())
+}
+
+#[async_trait]
+impl StockAdjustmentRepository for PostgresStockAdjustmentRepository {
+    async fn actor_role(&self, actor_id: Uuid) -> Result<Option<CustomerRole>> {
+        sqlx::query_scalar::<_, CustomerRole>("SELECT role FROM customers WHERE id = $1")
+            .bind(actor_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get user role: {}", e)))
+    }
+
+    async fn find_approvers(&self, roles: &[CustomerRole]) -> Result<Vec<(Uuid, String)>> {
+        let roles: Vec<String> = roles
+            .iter()
+            .map(|role| format!("{:?}", role).to_lowercase())
+            .collect();
+
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, email FROM customers WHERE role::text = ANY($1) ORDER BY email")
+            .bind(&roles)
+            .fetch_all(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to list approvers: {}", e)))
+    }
+
+    async fn current_quantity(&self, product_id: Uuid, variant_id: Option<Uuid>, location_id: Uuid) -> Result<Option<i32>> {
+        sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT available_quantity FROM inventory_levels
+            WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND location_id = $3
+            "#
+        )
+        .bind(product_id)
+        .bind(variant_id)
+        .bind(location_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get inventory level: {}", e)))
+    }
+
+    async fn create(
+        &self,
+        request: &CreateStockAdjustmentRequest,
+        requested_by: Uuid,
+        quantity_at_request: i32,
+        requires_approval: bool,
+    ) -> Result<StockAdjustmentRequest> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let adjustment = sqlx::query_as::<_, StockAdjustmentRequest>(
+            r#"
+            INSERT INTO stock_adjustment_requests
+                (product_id, variant_id, location_id, quantity_change, quantity_at_request,
+                 reason, notes, requires_approval, requested_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#
+        )
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(request.location_id)
+        .bind(request.quantity_change)
+        .bind(quantity_at_request)
+        .bind(&request.reason)
+        .bind(&request.notes)
+        .bind(requires_approval)
+        .bind(requested_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create stock adjustment: {}", e)))?;
+
+        record_audit(&mut tx, adjustment.id, "requested", Some(requested_by), request.notes.as_deref()).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(adjustment)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<StockAdjustmentRequest>> {
+        sqlx::query_as::<_, StockAdjustmentRequest>("SELECT * FROM stock_adjustment_requests WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get stock adjustment: {}", e)))
+    }
+
+    async fn list(&self, status: Option<StockAdjustmentStatus>, limit: i64) -> Result<Vec<StockAdjustmentRequest>> {
+        sqlx::query_as::<_, StockAdjustmentRequest>(
+            r#"
+            SELECT * FROM stock_adjustment_requests
+            WHERE $1::stock_adjustment_status IS NULL OR status = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list stock adjustments: {}", e)))
+    }
+
+    async fn audit_trail(&self, id: Uuid) -> Result<Vec<StockAdjustmentAuditEntry>> {
+        sqlx::query_as::<_, StockAdjustmentAuditEntry>(
+            "SELECT * FROM stock_adjustment_audit WHERE adjustment_id = $1 ORDER BY created_at, id"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get stock adjustment audit trail: {}", e)))
+    }
+
+    async fn post(&self, id: Uuid, approved_by: Option<Uuid>, notes: Option<&str>) -> Result<StockAdjustmentRequest> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let adjustment = sqlx::query_as::<_, StockAdjustmentRequest>(
+            "SELECT * FROM stock_adjustment_requests WHERE id = $1 AND status = 'pending' FOR UPDATE"
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get stock adjustment: {}", e)))?
+        .ok_or_else(|| Error::validation("Stock adjustment is no longer pending"))?;
+
+        let (level_id, before) = sqlx::query_as::<_, (Uuid, i32)>(
+            r#"
+            SELECT id, available_quantity FROM inventory_levels
+            WHERE product_id = $1 AND variant_id IS NOT DISTINCT FROM $2 AND location_id = $3
+            FOR UPDATE
+            "#
+        )
+        .bind(adjustment.product_id)
+        .bind(adjustment.variant_id)
+        .bind(adjustment.location_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get inventory level: {}", e)))?
+        .ok_or_else(|| Error::not_found("No inventory level for this product at this location"))?;
+
+        // Stock may have moved since the request; the change applies to the current level
+        let after = before + adjustment.quantity_change;
+        if after < 0 {
+            return Err(Error::validation(format!(
+                "Adjustment would leave negative stock ({} available)",
+                before
+            )));
+        }
+
+        sqlx::query("UPDATE inventory_levels SET available_quantity = $2, updated_at = NOW() WHERE id = $1")
+            .bind(level_id)
+            .bind(after)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update inventory level: {}", e)))?;
+
+        let movement_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO stock_movements (product_id, variant_id, location_id, quantity, movement_type, reference, notes)
+            VALUES ($1, $2, $3, $4, 'adjustment', $5, $6)
+            RETURNING id
+            "#
+        )
+        .bind(adjustment.product_id)
+        .bind(adjustment.variant_id)
+        .bind(adjustment.location_id)
+        .bind(adjustment.quantity_change)
+        .bind(format!("stock_adjustment:{}", adjustment.id))
+        .bind(&adjustment.reason)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record stock movement: {}", e)))?;
+
+        let adjustment = sqlx::query_as::<_, StockAdjustmentRequest>(
+            r#"
+            UPDATE stock_adjustment_requests
+            SET status = 'approved',
+                quantity_before = $2,
+                quantity_after = $3,
+                stock_movement_id = $4,
+                decided_by = $5,
+                decided_at = NOW(),
+                decision_notes = $6
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(before)
+        .bind(after)
+        .bind(movement_id)
+        .bind(approved_by)
+        .bind(notes)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update stock adjustment: {}", e)))?;
+
+        record_audit(&mut tx, id, "approved", approved_by, notes).await?;
+        record_audit(&mut tx, id, "posted", None, Some(&format!("{} -> {}", before, after))).await?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(adjustment)
+    }
+
+    async fn close(
+        &self,
+        id: Uuid,
+        status: StockAdjustmentStatus,
+        actor_id: Uuid,
+        notes: Option<&str>,
+    ) -> Result<Option<StockAdjustmentRequest>> {
+        let action = match status {
+            StockAdjustmentStatus::Rejected => "rejected",
+            StockAdjustmentStatus::Cancelled => "cancelled",
+            _ => return Err(Error::validation("Adjustments can only be closed as rejected or cancelled")),
+        };
+
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let adjustment = sqlx::query_as::<_, StockAdjustmentRequest>(
+            r#"
+            UPDATE stock_adjustment_requests
+            SET status = $2, decided_by = $3, decided_at = NOW(), decision_notes = $4
+            WHERE id = $1 AND status = 'pending'
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(status)
+        .bind(actor_id)
+        .bind(notes)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update stock adjustment: {}", e)))?;
+
+        if adjustment.is_some() {
+            record_audit(&mut tx, id, action, Some(actor_id), notes).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(adjustment)
+    }
+}
@@ -0,0 +1,111 @@
+//! Subscription plan repository
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    subscriptions::{CreateSubscriptionPlanRequest, SubscriptionPlan, UpdateSubscriptionPlanRequest},
+};
+
+/// Repository trait for subscription plans
+#[async_trait]
+pub trait SubscriptionPlanRepository: Send + Sync {
+    /// Create a plan
+    async fn create(&self, request: &CreateSubscriptionPlanRequest) -> Result<SubscriptionPlan>;
+
+    /// Get a plan
+    async fn find(&self, id: Uuid) -> Result<Option<SubscriptionPlan>>;
+
+    /// List plans by name
+    async fn list(&self, active_only: bool) -> Result<Vec<SubscriptionPlan>>;
+
+    /// Update a plan; None if it does not exist
+    async fn update(&self, id: Uuid, request: &UpdateSubscriptionPlanRequest) -> Result<Option<SubscriptionPlan>>;
+}
+
+/// PostgreSQL implementation of SubscriptionPlanRepository
+pub struct PostgresSubscriptionPlanRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresSubscriptionPlanRepository {
+    /// Create a new PostgreSQL subscription plan repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SubscriptionPlanRepository for PostgresSubscriptionPlanRepository {
+    async fn create(&self, request: &CreateSubscriptionPlanRequest) -> Result<SubscriptionPlan> {
+        sqlx::query_as::<_, SubscriptionPlan>(
+            r#"
+            INSERT INTO subscription_plans
+                (name, description, product_id, variant_id, interval, interval_count,
+                 currency, amount, setup_fee, trial_days, min_cycles, max_cycles)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING *
+            "#
+        )
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(request.product_id)
+        .bind(request.variant_id)
+        .bind(request.interval)
+        .bind(request.interval_count)
+        .bind(request.currency)
+        .bind(request.amount)
+        .bind(request.setup_fee)
+        .bind(request.trial_days.unwrap_or(0))
+        .bind(request.min_cycles)
+        .bind(request.max_cycles)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create subscription plan: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<SubscriptionPlan>> {
+        sqlx::query_as::<_, SubscriptionPlan>("SELECT * FROM subscription_plans WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get subscription plan: {}", e)))
+    }
+
+    async fn list(&self, active_only: bool) -> Result<Vec<SubscriptionPlan>> {
+        sqlx::query_as::<_, SubscriptionPlan>(
+            "SELECT * FROM subscription_plans WHERE (NOT $1 OR is_active) ORDER BY name, created_at"
+        )
+        .bind(active_only)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list subscription plans: {}", e)))
+    }
+
+    async fn update(&self, id: Uuid, request: &UpdateSubscriptionPlanRequest) -> Result<Option<SubscriptionPlan>> {
+        sqlx::query_as::<_, SubscriptionPlan>(
+            r#"
+            UPDATE subscription_plans
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                amount = COALESCE($4, amount),
+                setup_fee = COALESCE($5, setup_fee),
+                trial_days = COALESCE($6, trial_days),
+                is_active = COALESCE($7, is_active)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&request.name)
+        .bind(&request.description)
+        .bind(request.amount)
+        .bind(request.setup_fee)
+        .bind(request.trial_days)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update subscription plan: {}", e)))
+    }
+}
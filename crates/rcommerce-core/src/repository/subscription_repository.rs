@@ -82,7 +82,10 @@ pub trait SubscriptionRepository: Send + Sync + 'static {
     /// Mark invoice as failed
     async fn mark_invoice_failed(&self, invoice_id: Uuid, failure_reason: String) -> Result<()>;
     
-    /// Get pending invoices
+    /// Set when a failed invoice is next retried
+    async fn schedule_invoice_retry(&self, invoice_id: Uuid, next_retry_at: DateTime<Utc>) -> Result<()>;
+    
+    /// Get unpaid invoices (pending, billed or failed)
     async fn get_pending_invoices(&self) -> Result<Vec<SubscriptionInvoice>>;
     
     // Dunning/payment retry operations
@@ -150,8 +153,8 @@ impl SubscriptionRepository for PostgresSubscriptionRepository {
                 current_cycle, min_cycles, max_cycles,
                 starts_at, next_billing_at,
                 payment_method_id, gateway, notes,
-                created_at, updated_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $21)
+                created_at, updated_at, plan_id
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $21, $22)
             RETURNING *
             "#
         )
@@ -176,6 +179,7 @@ impl SubscriptionRepository for PostgresSubscriptionRepository {
         .bind(request.gateway)
         .bind(request.notes)
         .bind(now)
+        .bind(request.plan_id)
         .fetch_one(&self.pool)
         .await
         .map_err(Error::Database)?;
@@ -531,9 +535,26 @@ impl SubscriptionRepository for PostgresSubscriptionRepository {
         Ok(())
     }
     
+    async fn schedule_invoice_retry(&self, invoice_id: Uuid, next_retry_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE subscription_invoices 
+            SET next_retry_at = $1, retry_count = retry_count + 1, updated_at = NOW()
+            WHERE id = $2
+            "#
+        )
+        .bind(next_retry_at)
+        .bind(invoice_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+        
+        Ok(())
+    }
+    
     async fn get_pending_invoices(&self) -> Result<Vec<SubscriptionInvoice>> {
         let invoices = sqlx::query_as::<_, SubscriptionInvoice>(
-            "SELECT * FROM subscription_invoices WHERE status IN ('pending', 'billed') ORDER BY created_at ASC"
+            "SELECT * FROM subscription_invoices WHERE status IN ('pending', 'billed', 'failed') ORDER BY created_at ASC"
         )
         .fetch_all(&self.pool)
         .await
@@ -0,0 +1,425 @@
+//! Supplier feed repository
+//!
+//! Supplier feeds, the supplier products their rows are matched to and the
+//! dropship orders paid orders are routed into. A feed's changes to its
+//! products are applied in one transaction.
+
+use async_trait::async_trait;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    inventory::purchasing::Supplier,
+    inventory::supplier_feed::{
+        DropshipLine, DropshipOrder, DropshipOrderDetail, DropshipOrderItem, DropshipStatus, FeedProduct,
+        FeedSyncReport, FeedUpdate, SaveSupplierFeedRequest, SupplierFeed,
+    },
+};
+
+/// Repository trait for supplier feeds and dropship orders
+#[async_trait]
+pub trait SupplierFeedRepository: Send + Sync {
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>>;
+
+    async fn find_feed(&self, supplier_id: Uuid) -> Result<Option<SupplierFeed>>;
+
+    /// Create or replace a supplier's feed
+    async fn save_feed(&self, supplier_id: Uuid, request: &SaveSupplierFeedRequest, webhook_secret: &str) -> Result<SupplierFeed>;
+
+    /// Remove a supplier's feed; false if it has none
+    async fn delete_feed(&self, supplier_id: Uuid) -> Result<bool>;
+
+    /// Active feeds with a source URL not synced within their interval
+    async fn due_feeds(&self) -> Result<Vec<SupplierFeed>>;
+
+    /// The supplier's products with a supplier SKU
+    async fn feed_products(&self, supplier_id: Uuid) -> Result<Vec<FeedProduct>>;
+
+    /// Record stock and costs and change products and variants
+    async fn apply_updates(&self, updates: &[FeedUpdate]) -> Result<()>;
+
+    /// Record a sync attempt; a failed one keeps the last report
+    async fn record_sync(&self, feed_id: Uuid, report: Option<&FeedSyncReport>, error: Option<&str>) -> Result<()>;
+
+    /// Paid order lines of dropshipped products not routed yet
+    async fn unrouted_lines(&self) -> Result<Vec<DropshipLine>>;
+
+    /// Route lines of an order to a supplier; returns the dropship order's ID
+    async fn create_dropship_order(&self, order_id: Uuid, supplier_id: Uuid, lines: &[DropshipLine]) -> Result<Uuid>;
+
+    async fn dropship_order_detail(&self, id: Uuid) -> Result<Option<DropshipOrderDetail>>;
+
+    /// Dropship orders, newest first
+    async fn list_dropship_orders(
+        &self,
+        status: Option<DropshipStatus>,
+        supplier_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<DropshipOrder>>;
+
+    /// Pending orders, and failed ones tried fewer than `max_attempts` times
+    async fn sendable_dropship_orders(&self, max_attempts: i32) -> Result<Vec<DropshipOrder>>;
+
+    async fn mark_dropship_sent(&self, id: Uuid, supplier_reference: Option<&str>) -> Result<()>;
+
+    async fn mark_dropship_failed(&self, id: Uuid, error: &str) -> Result<()>;
+}
+
+/// PostgreSQL implementation of SupplierFeedRepository
+#[derive(Clone)]
+pub struct PostgresSupplierFeedRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresSupplierFeedRepository {
+    /// Create a new PostgreSQL supplier feed repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SupplierFeedRepository for PostgresSupplierFeedRepository {
+    async fn find_supplier(&self, id: Uuid) -> Result<Option<Supplier>> {
+        sqlx::query_as::<_, Supplier>("SELECT * FROM suppliers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get supplier: {}", e)))
+    }
+
+    async fn find_feed(&self, supplier_id: Uuid) -> Result<Option<SupplierFeed>> {
+        sqlx::query_as::<_, SupplierFeed>("SELECT * FROM supplier_feeds WHERE supplier_id = $1")
+            .bind(supplier_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get supplier feed: {}", e)))
+    }
+
+    async fn save_feed(&self, supplier_id: Uuid, request: &SaveSupplierFeedRequest, webhook_secret: &str) -> Result<SupplierFeed> {
+        sqlx::query_as::<_, SupplierFeed>(
+            r#"
+            INSERT INTO supplier_feeds
+                (supplier_id, format, source_url, pull_interval_minutes, webhook_secret, mapping, margin_rules,
+                 update_prices, auto_disable, dropship, order_webhook_url, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (supplier_id) DO UPDATE SET
+                format = EXCLUDED.format,
+                source_url = EXCLUDED.source_url,
+                pull_interval_minutes = EXCLUDED.pull_interval_minutes,
+                webhook_secret = EXCLUDED.webhook_secret,
+                mapping = EXCLUDED.mapping,
+                margin_rules = EXCLUDED.margin_rules,
+                update_prices = EXCLUDED.update_prices,
+                auto_disable = EXCLUDED.auto_disable,
+                dropship = EXCLUDED.dropship,
+                order_webhook_url = EXCLUDED.order_webhook_url,
+                is_active = EXCLUDED.is_active
+            RETURNING *
+            "#
+        )
+        .bind(supplier_id)
+        .bind(request.format)
+        .bind(&request.source_url)
+        .bind(request.pull_interval_minutes)
+        .bind(webhook_secret)
+        .bind(Json(&request.mapping))
+        .bind(Json(&request.margin_rules))
+        .bind(request.update_prices)
+        .bind(request.auto_disable)
+        .bind(request.dropship)
+        .bind(&request.order_webhook_url)
+        .bind(request.is_active)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save supplier feed: {}", e)))
+    }
+
+    async fn delete_feed(&self, supplier_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM supplier_feeds WHERE supplier_id = $1")
+            .bind(supplier_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to delete supplier feed: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn due_feeds(&self) -> Result<Vec<SupplierFeed>> {
+        sqlx::query_as::<_, SupplierFeed>(
+            r#"
+            SELECT * FROM supplier_feeds
+            WHERE is_active AND source_url IS NOT NULL
+              AND (last_synced_at IS NULL
+                   OR last_synced_at <= NOW() - make_interval(mins => pull_interval_minutes))
+            ORDER BY last_synced_at NULLS FIRST
+            "#
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list due supplier feeds: {}", e)))
+    }
+
+    async fn feed_products(&self, supplier_id: Uuid) -> Result<Vec<FeedProduct>> {
+        sqlx::query_as::<_, FeedProduct>(
+            r#"
+            SELECT sp.id AS supplier_product_id, sp.product_id, sp.variant_id, sp.supplier_sku, sp.unit_cost,
+                   sp.disabled_by_feed,
+                   COALESCE(v.is_active, p.is_active) AS is_active,
+                   COALESCE(v.price, p.price) AS price
+            FROM supplier_products sp
+            JOIN products p ON p.id = sp.product_id
+            LEFT JOIN product_variants v ON v.id = sp.variant_id
+            WHERE sp.supplier_id = $1 AND sp.supplier_sku IS NOT NULL
+            "#
+        )
+        .bind(supplier_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list feed products: {}", e)))
+    }
+
+    async fn apply_updates(&self, updates: &[FeedUpdate]) -> Result<()> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        for update in updates {
+            sqlx::query(
+                r#"
+                UPDATE supplier_products
+                SET supplier_stock = $2, unit_cost = $3, disabled_by_feed = $4, stock_synced_at = NOW()
+                WHERE id = $1
+                "#
+            )
+            .bind(update.supplier_product_id)
+            .bind(update.supplier_stock)
+            .bind(update.unit_cost)
+            .bind(update.disabled_by_feed)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update supplier product: {}", e)))?;
+
+            if update.inventory_quantity.is_none() && update.price.is_none() && update.is_active.is_none() {
+                continue;
+            }
+            let (table, id) = match update.variant_id {
+                Some(variant_id) => ("product_variants", variant_id),
+                None => ("products", update.product_id),
+            };
+            sqlx::query(&format!(
+                r#"
+                UPDATE {}
+                SET inventory_quantity = COALESCE($2, inventory_quantity),
+                    price = COALESCE($3, price),
+                    is_active = COALESCE($4, is_active),
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+                table
+            ))
+            .bind(id)
+            .bind(update.inventory_quantity)
+            .bind(update.price)
+            .bind(update.is_active)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update product from feed: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit feed updates: {}", e)))
+    }
+
+    async fn record_sync(&self, feed_id: Uuid, report: Option<&FeedSyncReport>, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE supplier_feeds
+            SET last_synced_at = NOW(), last_error = $3, last_report = COALESCE($2, last_report)
+            WHERE id = $1
+            "#
+        )
+        .bind(feed_id)
+        .bind(report.map(Json))
+        .bind(error)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record supplier feed sync: {}", e)))?;
+        Ok(())
+    }
+
+    async fn unrouted_lines(&self) -> Result<Vec<DropshipLine>> {
+        // A line goes to one supplier: the variant's before the product's,
+        // then the preferred one
+        sqlx::query_as::<_, DropshipLine>(
+            r#"
+            SELECT DISTINCT ON (oi.id)
+                   oi.id AS order_item_id, oi.order_id, sf.supplier_id, sp.supplier_sku, oi.title, oi.quantity,
+                   sp.unit_cost
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            JOIN supplier_products sp
+              ON sp.product_id = oi.product_id AND (sp.variant_id IS NULL OR sp.variant_id = oi.variant_id)
+            JOIN supplier_feeds sf ON sf.supplier_id = sp.supplier_id AND sf.dropship AND sf.is_active
+            WHERE o.payment_status = 'paid'
+              AND o.status NOT IN ('cancelled', 'refunded')
+              AND o.created_at >= sf.created_at
+              AND oi.quantity > 0
+              AND NOT EXISTS (SELECT 1 FROM dropship_order_items d WHERE d.order_item_id = oi.id)
+            ORDER BY oi.id, sp.variant_id IS NULL, sp.is_preferred DESC
+            LIMIT 1000
+            "#
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list unrouted order lines: {}", e)))
+    }
+
+    async fn create_dropship_order(&self, order_id: Uuid, supplier_id: Uuid, lines: &[DropshipLine]) -> Result<Uuid> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO dropship_orders (order_id, supplier_id)
+            VALUES ($1, $2)
+            ON CONFLICT (order_id, supplier_id) DO UPDATE SET updated_at = NOW()
+            RETURNING id
+            "#
+        )
+        .bind(order_id)
+        .bind(supplier_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create dropship order: {}", e)))?;
+
+        for line in lines {
+            sqlx::query(
+                r#"
+                INSERT INTO dropship_order_items (dropship_order_id, order_item_id, supplier_sku, title, quantity, unit_cost)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (order_item_id) DO NOTHING
+                "#
+            )
+            .bind(id)
+            .bind(line.order_item_id)
+            .bind(&line.supplier_sku)
+            .bind(&line.title)
+            .bind(line.quantity)
+            .bind(line.unit_cost)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to add dropship order line: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit dropship order: {}", e)))?;
+        Ok(id)
+    }
+
+    async fn dropship_order_detail(&self, id: Uuid) -> Result<Option<DropshipOrderDetail>> {
+        let Some(dropship_order) = sqlx::query_as::<_, DropshipOrder>("SELECT * FROM dropship_orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get dropship order: {}", e)))?
+        else {
+            return Ok(None);
+        };
+
+        let (order_number, email, shipping_address) =
+            sqlx::query_as::<_, (String, String, Option<serde_json::Value>)>(
+                "SELECT order_number, email, shipping_address FROM orders WHERE id = $1"
+            )
+            .bind(dropship_order.order_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get dropship order's order: {}", e)))?;
+
+        let items = sqlx::query_as::<_, DropshipOrderItem>(
+            "SELECT * FROM dropship_order_items WHERE dropship_order_id = $1 ORDER BY title"
+        )
+        .bind(id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list dropship order lines: {}", e)))?;
+
+        Ok(Some(DropshipOrderDetail {
+            dropship_order,
+            order_number,
+            email,
+            shipping_address,
+            items,
+        }))
+    }
+
+    async fn list_dropship_orders(
+        &self,
+        status: Option<DropshipStatus>,
+        supplier_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<DropshipOrder>> {
+        sqlx::query_as::<_, DropshipOrder>(
+            r#"
+            SELECT * FROM dropship_orders
+            WHERE ($1::dropship_order_status IS NULL OR status = $1)
+              AND ($2::uuid IS NULL OR supplier_id = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(status)
+        .bind(supplier_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list dropship orders: {}", e)))
+    }
+
+    async fn sendable_dropship_orders(&self, max_attempts: i32) -> Result<Vec<DropshipOrder>> {
+        sqlx::query_as::<_, DropshipOrder>(
+            r#"
+            SELECT * FROM dropship_orders
+            WHERE status = 'pending' OR (status = 'failed' AND attempts < $1)
+            ORDER BY created_at
+            LIMIT 100
+            "#
+        )
+        .bind(max_attempts)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list dropship orders to send: {}", e)))
+    }
+
+    async fn mark_dropship_sent(&self, id: Uuid, supplier_reference: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE dropship_orders
+            SET status = 'sent', attempts = attempts + 1, last_error = NULL,
+                supplier_reference = $2, sent_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(supplier_reference)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark dropship order sent: {}", e)))?;
+        Ok(())
+    }
+
+    async fn mark_dropship_failed(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE dropship_orders
+            SET status = 'failed', attempts = attempts + 1, last_error = $2
+            WHERE id = $1
+            "#
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to mark dropship order failed: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Tracking repository: shipments to track and their carrier status
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    shipping::tracking::{TrackedShipment, TrackingRecord},
+    Error, Result,
+};
+
+/// Repository trait for shipment tracking
+#[async_trait]
+pub trait TrackingRepository: Send + Sync {
+    async fn find(&self, shipment_id: Uuid) -> Result<Option<TrackedShipment>>;
+
+    /// Live shipments with this provider's tracking number
+    async fn find_by_tracking_number(&self, provider: &str, tracking_number: &str) -> Result<Vec<TrackedShipment>>;
+
+    /// Shipments of these providers not yet delivered, with labels bought
+    /// since `since`, checked longest ago first
+    async fn shipments_to_poll(
+        &self,
+        providers: &[String],
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<TrackedShipment>>;
+
+    /// Save the carrier's status, moving the shipment (and its order's
+    /// fulfillment status) to `status` if set
+    async fn record(&self, shipment_id: Uuid, record: &TrackingRecord) -> Result<()>;
+
+    /// Mark a shipment checked without an update (its carrier failed)
+    async fn mark_checked(&self, shipment_id: Uuid) -> Result<()>;
+}
+
+/// PostgreSQL implementation of TrackingRepository
+pub struct PostgresTrackingRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresTrackingRepository {
+    /// Create a new PostgreSQL tracking repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+const TRACKED_SHIPMENT_SQL: &str = r#"
+    SELECT f.id, f.order_id, o.order_number, o.email,
+           COALESCE(c.email_notifications, true) AS email_notifications,
+           l.code AS location_code, f.tracking_company, f.tracking_number, f.tracking_url, f.status
+    FROM fulfillments f
+    JOIN orders o ON o.id = f.order_id
+    LEFT JOIN customers c ON c.id = o.customer_id
+    LEFT JOIN inventory_locations l ON l.id = f.location_id
+"#;
+
+#[async_trait]
+impl TrackingRepository for PostgresTrackingRepository {
+    async fn find(&self, shipment_id: Uuid) -> Result<Option<TrackedShipment>> {
+        sqlx::query_as::<_, TrackedShipment>(&format!("{} WHERE f.id = $1", TRACKED_SHIPMENT_SQL))
+            .bind(shipment_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get shipment: {}", e)))
+    }
+
+    async fn find_by_tracking_number(&self, provider: &str, tracking_number: &str) -> Result<Vec<TrackedShipment>> {
+        sqlx::query_as::<_, TrackedShipment>(&format!(
+            r#"{}
+            WHERE f.tracking_company = $1 AND f.tracking_number = $2
+              AND f.status NOT IN ('cancelled', 'returned')
+            "#,
+            TRACKED_SHIPMENT_SQL
+        ))
+        .bind(provider)
+        .bind(tracking_number)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to find shipments by tracking number: {}", e)))
+    }
+
+    async fn shipments_to_poll(
+        &self,
+        providers: &[String],
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<TrackedShipment>> {
+        sqlx::query_as::<_, TrackedShipment>(&format!(
+            r#"{}
+            WHERE f.tracking_number IS NOT NULL
+              AND f.tracking_company = ANY($1)
+              AND f.status IN ('pending', 'processing', 'shipped')
+              AND f.created_at >= $2
+            ORDER BY f.tracking_checked_at NULLS FIRST, f.created_at
+            LIMIT $3
+            "#,
+            TRACKED_SHIPMENT_SQL
+        ))
+        .bind(providers)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list shipments to track: {}", e)))
+    }
+
+    async fn record(&self, shipment_id: Uuid, record: &TrackingRecord) -> Result<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        let order_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE fulfillments
+            SET tracking_status = $2,
+                tracking_detail = COALESCE($3, tracking_detail),
+                estimated_delivery = COALESCE($4, estimated_delivery),
+                tracking_checked_at = NOW(),
+                status = COALESCE($5, status),
+                shipped_at = CASE WHEN $5 IN ('shipped', 'delivered') THEN COALESCE(shipped_at, NOW()) ELSE shipped_at END,
+                delivered_at = CASE WHEN $5 = 'delivered' THEN COALESCE(delivered_at, NOW()) ELSE delivered_at END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING order_id
+            "#
+        )
+        .bind(shipment_id)
+        .bind(record.tracking_status.as_str())
+        .bind(record.detail.as_deref())
+        .bind(record.estimated_delivery)
+        .bind(record.status)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to save tracking status: {}", e)))?
+        .ok_or_else(|| Error::not_found("Shipment not found"))?;
+
+        // The order is delivered once all its live shipments are
+        if record.status.is_some() {
+            sqlx::query(
+                r#"
+                UPDATE orders
+                SET fulfillment_status = shipments.status, updated_at = NOW()
+                FROM (
+                    SELECT CASE
+                        WHEN bool_and(status = 'delivered') THEN 'delivered'
+                        WHEN bool_and(status IN ('shipped', 'delivered')) THEN 'shipped'
+                        ELSE 'partial'
+                    END::fulfillment_status AS status
+                    FROM fulfillments
+                    WHERE order_id = $1 AND status NOT IN ('cancelled', 'returned')
+                    HAVING COUNT(*) > 0
+                ) shipments
+                WHERE orders.id = $1
+                "#
+            )
+            .bind(order_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update order fulfillment status: {}", e)))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to commit tracking status: {}", e)))
+    }
+
+    async fn mark_checked(&self, shipment_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE fulfillments SET tracking_checked_at = NOW() WHERE id = $1")
+            .bind(shipment_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to mark shipment checked: {}", e)))?;
+        Ok(())
+    }
+}
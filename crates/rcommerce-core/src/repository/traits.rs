@@ -8,6 +8,7 @@ use uuid::Uuid;
 use crate::{
     Result, Pagination, SortParams,
     models::{
+        Cursor,
         Product, ProductVariant, ProductImage, ProductFilter,
         CreateProductRequest, UpdateProductRequest,
         Customer, CreateCustomerRequest, UpdateCustomerRequest,
@@ -31,6 +32,14 @@ pub trait ProductRepositoryTrait: Send + Sync + 'static {
         sort: Option<&SortParams>,
     ) -> Result<Vec<Product>>;
     
+    /// Up to `limit` products after a cursor, newest first
+    async fn find_after(
+        &self,
+        filter: &ProductFilter,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Product>>;
+    
     /// Find product by slug
     async fn find_by_slug(&self, slug: &str) -> Result<Option<Product>>;
     
@@ -43,7 +52,7 @@ pub trait ProductRepositoryTrait: Send + Sync + 'static {
     /// Update product
     async fn update(&self, id: Uuid, request: UpdateProductRequest) -> Result<Product>;
     
-    /// Delete product
+    /// Soft-delete product; it stays restorable until purged
     async fn delete(&self, id: Uuid) -> Result<bool>;
     
     /// Find variants for a product
@@ -65,13 +74,16 @@ pub trait CustomerRepositoryTrait: Send + Sync + 'static {
     /// List all customers
     async fn list(&self) -> Result<Vec<Customer>>;
     
+    /// Up to `limit` customers after a cursor, newest first
+    async fn list_after(&self, after: Option<&Cursor>, limit: i64) -> Result<Vec<Customer>>;
+    
     /// Create new customer
     async fn create(&self, request: CreateCustomerRequest) -> Result<Customer>;
     
     /// Update customer
     async fn update(&self, id: Uuid, request: UpdateCustomerRequest) -> Result<Customer>;
     
-    /// Delete customer
+    /// Soft-delete customer; it stays restorable until purged
     async fn delete(&self, id: Uuid) -> Result<bool>;
 }
 
@@ -0,0 +1,326 @@
+//! Two-factor authentication repository
+//!
+//! TOTP credentials, backup codes and login challenges.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{LoginDevice, TwoFactorChallenge, TwoFactorCredential, TwoFactorLoginKind},
+};
+
+/// Repository trait for two-factor authentication
+#[async_trait]
+pub trait TwoFactorRepository: Send + Sync {
+    /// A customer's credential, confirmed or not
+    async fn find(&self, customer_id: Uuid) -> Result<Option<TwoFactorCredential>>;
+
+    /// Store a new unconfirmed secret, replacing an earlier unconfirmed one;
+    /// false if 2FA is already enabled
+    async fn start_enrollment(&self, customer_id: Uuid, secret: &str) -> Result<bool>;
+
+    /// Enable 2FA with the step of the confirming code and a first set of
+    /// backup codes; false if it was not pending
+    async fn confirm(&self, customer_id: Uuid, step: i64, code_hashes: &[String]) -> Result<bool>;
+
+    /// Accept a code's step if it is later than the last one accepted, and
+    /// clear the failure count; false if the step was already used
+    async fn record_step(&self, customer_id: Uuid, step: i64) -> Result<bool>;
+
+    /// Count a wrong code, locking 2FA until `lock_until` at `max_failures`
+    async fn record_failure(&self, customer_id: Uuid, max_failures: u32, lock_until: DateTime<Utc>) -> Result<()>;
+
+    /// Remove the credential, backup codes and open challenges; false if there was none
+    async fn disable(&self, customer_id: Uuid) -> Result<bool>;
+
+    /// Replace all backup codes
+    async fn replace_backup_codes(&self, customer_id: Uuid, code_hashes: &[String]) -> Result<()>;
+
+    /// Use up a backup code and clear the failure count; false if there is no such unused code
+    async fn use_backup_code(&self, customer_id: Uuid, code_hash: &str) -> Result<bool>;
+
+    /// Unused backup codes
+    async fn remaining_backup_codes(&self, customer_id: Uuid) -> Result<i64>;
+
+    /// Open a challenge for a login that passed the password check
+    async fn create_challenge(
+        &self,
+        customer_id: Uuid,
+        token_hash: &str,
+        kind: TwoFactorLoginKind,
+        device: &LoginDevice,
+        expires_at: DateTime<Utc>,
+    ) -> Result<TwoFactorChallenge>;
+
+    async fn find_challenge(&self, token_hash: &str) -> Result<Option<TwoFactorChallenge>>;
+
+    /// Count a wrong code against a challenge; returns its attempts so far
+    async fn record_challenge_attempt(&self, id: Uuid) -> Result<i32>;
+
+    /// Mark a challenge used; false if it already was
+    async fn consume_challenge(&self, id: Uuid) -> Result<bool>;
+
+    /// Delete challenges that expired before `before`; returns how many
+    async fn purge_challenges(&self, before: DateTime<Utc>) -> Result<u64>;
+}
+
+/// PostgreSQL implementation of TwoFactorRepository
+pub struct PostgresTwoFactorRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresTwoFactorRepository {
+    /// Create a new PostgreSQL two-factor repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+async fn insert_backup_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    customer_id: Uuid,
+    code_hashes: &[String],
+) -> Result<()> {
+    sqlx::query("DELETE FROM two_factor_backup_codes WHERE customer_id = $1")
+        .bind(customer_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to delete backup codes: {}", e)))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO two_factor_backup_codes (customer_id, code_hash)
+        SELECT $1, UNNEST($2::varchar[])
+        "#
+    )
+    .bind(customer_id)
+    .bind(code_hashes)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| Error::Other(format!("Failed to store backup codes: {}", e)))?;
+    Ok(())
+}
+
+#[async_trait]
+impl TwoFactorRepository for PostgresTwoFactorRepository {
+    async fn find(&self, customer_id: Uuid) -> Result<Option<TwoFactorCredential>> {
+        sqlx::query_as::<_, TwoFactorCredential>("SELECT * FROM two_factor_credentials WHERE customer_id = $1")
+            .bind(customer_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get two-factor credential: {}", e)))
+    }
+
+    async fn start_enrollment(&self, customer_id: Uuid, secret: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO two_factor_credentials (customer_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT (customer_id) DO UPDATE
+            SET secret = EXCLUDED.secret, last_used_step = NULL, failed_attempts = 0,
+                locked_until = NULL, updated_at = NOW()
+            WHERE two_factor_credentials.confirmed_at IS NULL
+            "#
+        )
+        .bind(customer_id)
+        .bind(secret)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to start two-factor enrollment: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn confirm(&self, customer_id: Uuid, step: i64, code_hashes: &[String]) -> Result<bool> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE two_factor_credentials
+            SET confirmed_at = NOW(), last_used_step = $2, failed_attempts = 0,
+                locked_until = NULL, updated_at = NOW()
+            WHERE customer_id = $1 AND confirmed_at IS NULL
+            "#
+        )
+        .bind(customer_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to confirm two-factor enrollment: {}", e)))?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        insert_backup_codes(&mut tx, customer_id, code_hashes).await?;
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(true)
+    }
+
+    async fn record_step(&self, customer_id: Uuid, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE two_factor_credentials
+            SET last_used_step = $2, failed_attempts = 0, locked_until = NULL, updated_at = NOW()
+            WHERE customer_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#
+        )
+        .bind(customer_id)
+        .bind(step)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record two-factor code: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_failure(&self, customer_id: Uuid, max_failures: u32, lock_until: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE two_factor_credentials
+            SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0 ELSE failed_attempts + 1 END,
+                locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN $3 ELSE locked_until END,
+                updated_at = NOW()
+            WHERE customer_id = $1
+            "#
+        )
+        .bind(customer_id)
+        .bind(max_failures as i32)
+        .bind(lock_until)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record two-factor failure: {}", e)))?;
+        Ok(())
+    }
+
+    async fn disable(&self, customer_id: Uuid) -> Result<bool> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+
+        for table in ["two_factor_backup_codes", "two_factor_challenges"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE customer_id = $1", table))
+                .bind(customer_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to disable two-factor authentication: {}", e)))?;
+        }
+        let result = sqlx::query("DELETE FROM two_factor_credentials WHERE customer_id = $1")
+            .bind(customer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to disable two-factor authentication: {}", e)))?;
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn replace_backup_codes(&self, customer_id: Uuid, code_hashes: &[String]) -> Result<()> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to begin transaction: {}", e)))?;
+        insert_backup_codes(&mut tx, customer_id, code_hashes).await?;
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))
+    }
+
+    async fn use_backup_code(&self, customer_id: Uuid, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE two_factor_backup_codes SET used_at = NOW()
+            WHERE customer_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#
+        )
+        .bind(customer_id)
+        .bind(code_hash)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to use backup code: {}", e)))?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE two_factor_credentials SET failed_attempts = 0, locked_until = NULL, updated_at = NOW() WHERE customer_id = $1"
+        )
+        .bind(customer_id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record backup code: {}", e)))?;
+        Ok(true)
+    }
+
+    async fn remaining_backup_codes(&self, customer_id: Uuid) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM two_factor_backup_codes WHERE customer_id = $1 AND used_at IS NULL"
+        )
+        .bind(customer_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to count backup codes: {}", e)))
+    }
+
+    async fn create_challenge(
+        &self,
+        customer_id: Uuid,
+        token_hash: &str,
+        kind: TwoFactorLoginKind,
+        device: &LoginDevice,
+        expires_at: DateTime<Utc>,
+    ) -> Result<TwoFactorChallenge> {
+        sqlx::query_as::<_, TwoFactorChallenge>(
+            r#"
+            INSERT INTO two_factor_challenges (customer_id, token_hash, login_kind, user_agent, ip_address, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#
+        )
+        .bind(customer_id)
+        .bind(token_hash)
+        .bind(kind.as_str())
+        .bind(&device.user_agent)
+        .bind(&device.ip_address)
+        .bind(expires_at)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create two-factor challenge: {}", e)))
+    }
+
+    async fn find_challenge(&self, token_hash: &str) -> Result<Option<TwoFactorChallenge>> {
+        sqlx::query_as::<_, TwoFactorChallenge>("SELECT * FROM two_factor_challenges WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get two-factor challenge: {}", e)))
+    }
+
+    async fn record_challenge_attempt(&self, id: Uuid) -> Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            "UPDATE two_factor_challenges SET attempts = attempts + 1 WHERE id = $1 RETURNING attempts"
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record two-factor attempt: {}", e)))
+    }
+
+    async fn consume_challenge(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE two_factor_challenges SET consumed_at = NOW() WHERE id = $1 AND consumed_at IS NULL"
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to consume two-factor challenge: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_challenges(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM two_factor_challenges WHERE expires_at < $1")
+            .bind(before)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to purge two-factor challenges: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
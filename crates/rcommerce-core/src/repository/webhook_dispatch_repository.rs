@@ -0,0 +1,106 @@
+//! Webhook dispatch repository
+//!
+//! The endpoints subscribed to an event and the log of what was sent to
+//! them. Deliveries are logged in `webhook_deliveries` like test deliveries
+//! and replays, so they can be replayed later.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    repository::{ReplayDelivery, WebhookTarget},
+    services::WebhookTransform,
+};
+
+/// Repository trait for webhook dispatch
+#[async_trait]
+pub trait WebhookDispatchRepository: Send + Sync {
+    /// Active endpoints subscribed to an event type
+    async fn subscribed(&self, event_type: &str) -> Result<Vec<WebhookTarget>>;
+
+    /// Log a delivery and stamp the endpoint's last trigger and error
+    async fn record_delivery(
+        &self,
+        webhook_id: Uuid,
+        event_type: &str,
+        payload: &serde_json::Value,
+        delivery: &ReplayDelivery,
+    ) -> Result<()>;
+}
+
+/// PostgreSQL implementation of WebhookDispatchRepository
+pub struct PostgresWebhookDispatchRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresWebhookDispatchRepository {
+    /// Create a new PostgreSQL webhook dispatch repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl WebhookDispatchRepository for PostgresWebhookDispatchRepository {
+    async fn subscribed(&self, event_type: &str) -> Result<Vec<WebhookTarget>> {
+        let rows = sqlx::query_as::<_, (Uuid, String, String, Option<String>, sqlx::types::Json<HashMap<String, String>>)>(
+            "SELECT id, url, secret, payload_template, headers FROM webhooks WHERE is_active AND $1 = ANY(events::TEXT[])"
+        )
+        .bind(event_type)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list subscribed webhooks: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, url, secret, payload_template, headers)| WebhookTarget {
+                id,
+                url,
+                secret,
+                is_active: true,
+                transform: WebhookTransform { payload_template, headers: headers.0 },
+            })
+            .collect())
+    }
+
+    async fn record_delivery(
+        &self,
+        webhook_id: Uuid,
+        event_type: &str,
+        payload: &serde_json::Value,
+        delivery: &ReplayDelivery,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (webhook_id, event_type, payload, response_status, response_body, error_message, delivered_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $4::int BETWEEN 200 AND 299 THEN NOW() END)
+            "#
+        )
+        .bind(webhook_id)
+        .bind(event_type)
+        .bind(payload)
+        .bind(delivery.status)
+        .bind(&delivery.body)
+        .bind(&delivery.error)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record webhook delivery: {}", e)))?;
+
+        let error = match (&delivery.error, delivery.status) {
+            (Some(error), _) => Some(error.clone()),
+            (None, Some(status)) if !delivery.is_success() => Some(format!("HTTP {}", status)),
+            _ => None,
+        };
+        sqlx::query("UPDATE webhooks SET last_triggered_at = NOW(), last_error = $2 WHERE id = $1")
+            .bind(webhook_id)
+            .bind(error)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update webhook: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+//! Webhook replay repository
+//!
+//! Replayable events are the endpoint's original (non-replayed) deliveries.
+//! Retries of the same event share a payload, so each distinct payload is
+//! replayed once, in the order it was first sent.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    Result, Error,
+    models::{CreateWebhookReplayRequest, ReplayableEvent, WebhookReplay, WebhookReplayStatus},
+    services::WebhookTransform,
+};
+
+/// Endpoint a replay delivers to
+#[derive(Debug, Clone)]
+pub struct WebhookTarget {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub is_active: bool,
+    pub transform: WebhookTransform,
+}
+
+/// Outcome of one replayed delivery
+#[derive(Debug, Clone)]
+pub struct ReplayDelivery {
+    pub status: Option<i32>,
+    pub body: Option<String>,
+    pub error: Option<String>,
+}
+
+impl ReplayDelivery {
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, Some(200..=299))
+    }
+}
+
+/// Repository trait for webhook replays
+#[async_trait]
+pub trait WebhookReplayRepository: Send + Sync {
+    /// Get the endpoint to deliver to
+    async fn find_target(&self, webhook_id: Uuid) -> Result<Option<WebhookTarget>>;
+
+    /// Count the events a request would replay
+    async fn count_events(&self, webhook_id: Uuid, request: &CreateWebhookReplayRequest) -> Result<i64>;
+
+    /// Create a pending replay
+    async fn create(
+        &self,
+        webhook_id: Uuid,
+        request: &CreateWebhookReplayRequest,
+        rate_per_second: u32,
+        total_events: i64,
+        requested_by: Option<&str>,
+    ) -> Result<WebhookReplay>;
+
+    /// Get a replay
+    async fn find(&self, id: Uuid) -> Result<Option<WebhookReplay>>;
+
+    /// List an endpoint's replays, newest first
+    async fn list_for_webhook(&self, webhook_id: Uuid, limit: i64) -> Result<Vec<WebhookReplay>>;
+
+    /// Next events after the replay's cursor
+    async fn next_events(&self, replay: &WebhookReplay, limit: i64) -> Result<Vec<ReplayableEvent>>;
+
+    /// Log a replayed delivery and advance the replay's cursor and counters
+    async fn record_delivery(
+        &self,
+        replay: &WebhookReplay,
+        event: &ReplayableEvent,
+        payload: &serde_json::Value,
+        delivery: &ReplayDelivery,
+    ) -> Result<WebhookReplay>;
+
+    /// Move an unfinished replay to `status`, stamping start and finish
+    /// times. Returns None if the replay has already finished.
+    async fn set_status(&self, id: Uuid, status: WebhookReplayStatus, error: Option<&str>) -> Result<Option<WebhookReplay>>;
+}
+
+/// PostgreSQL implementation of WebhookReplayRepository
+pub struct PostgresWebhookReplayRepository {
+    db: sqlx::PgPool,
+}
+
+impl PostgresWebhookReplayRepository {
+    /// Create a new PostgreSQL webhook replay repository
+    pub fn new(db: sqlx::PgPool) -> Self {
+        Self { db }
+    }
+}
+
+/// Original deliveries matching a replay's filters, one per distinct payload.
+/// Parameters: webhook, from, to, event types, failed only.
+const REPLAYABLE_EVENTS: &str = r#"
+    SELECT DISTINCT ON (d.payload) d.id, d.event_type, d.payload, d.created_at
+    FROM webhook_deliveries d
+    WHERE d.webhook_id = $1
+      AND d.replay_id IS NULL
+      -- Deliveries from POST /webhooks/:id/test are not events
+      AND d.payload->'test' IS DISTINCT FROM 'true'::jsonb
+      AND d.created_at >= $2 AND d.created_at < $3
+      AND (cardinality($4::text[]) = 0 OR d.event_type = ANY($4))
+      AND (NOT $5 OR NOT EXISTS (
+          SELECT 1 FROM webhook_deliveries s
+          WHERE s.webhook_id = d.webhook_id
+            AND (s.payload = d.payload OR s.original_delivery_id = d.id)
+            AND s.response_status BETWEEN 200 AND 299
+      ))
+    ORDER BY d.payload, d.created_at, d.id
+"#;
+
+#[async_trait]
+impl WebhookReplayRepository for PostgresWebhookReplayRepository {
+    async fn find_target(&self, webhook_id: Uuid) -> Result<Option<WebhookTarget>> {
+        let row = sqlx::query_as::<_, (String, String, bool, Option<String>, sqlx::types::Json<HashMap<String, String>>)>(
+            "SELECT url, secret, is_active, payload_template, headers FROM webhooks WHERE id = $1"
+        )
+        .bind(webhook_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get webhook: {}", e)))?;
+
+        Ok(row.map(|(url, secret, is_active, payload_template, headers)| WebhookTarget {
+            id: webhook_id,
+            url,
+            secret,
+            is_active,
+            transform: WebhookTransform { payload_template, headers: headers.0 },
+        }))
+    }
+
+    async fn count_events(&self, webhook_id: Uuid, request: &CreateWebhookReplayRequest) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM ({}) events", REPLAYABLE_EVENTS))
+            .bind(webhook_id)
+            .bind(request.from)
+            .bind(request.to)
+            .bind(&request.event_types)
+            .bind(request.failed_only)
+            .fetch_one(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to count replayable events: {}", e)))
+    }
+
+    async fn create(
+        &self,
+        webhook_id: Uuid,
+        request: &CreateWebhookReplayRequest,
+        rate_per_second: u32,
+        total_events: i64,
+        requested_by: Option<&str>,
+    ) -> Result<WebhookReplay> {
+        sqlx::query_as::<_, WebhookReplay>(
+            r#"
+            INSERT INTO webhook_replays
+                (webhook_id, event_types, from_time, to_time, failed_only, rate_per_second, total_events, requested_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#
+        )
+        .bind(webhook_id)
+        .bind(&request.event_types)
+        .bind(request.from)
+        .bind(request.to)
+        .bind(request.failed_only)
+        .bind(rate_per_second as i32)
+        .bind(total_events as i32)
+        .bind(requested_by)
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to create webhook replay: {}", e)))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<WebhookReplay>> {
+        sqlx::query_as::<_, WebhookReplay>("SELECT * FROM webhook_replays WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get webhook replay: {}", e)))
+    }
+
+    async fn list_for_webhook(&self, webhook_id: Uuid, limit: i64) -> Result<Vec<WebhookReplay>> {
+        sqlx::query_as::<_, WebhookReplay>(
+            "SELECT * FROM webhook_replays WHERE webhook_id = $1 ORDER BY created_at DESC LIMIT $2"
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to list webhook replays: {}", e)))
+    }
+
+    async fn next_events(&self, replay: &WebhookReplay, limit: i64) -> Result<Vec<ReplayableEvent>> {
+        sqlx::query_as::<_, ReplayableEvent>(&format!(
+            r#"
+            SELECT * FROM ({}) events
+            WHERE $6::timestamptz IS NULL OR (created_at, id) > ($6, $7)
+            ORDER BY created_at, id
+            LIMIT $8
+            "#,
+            REPLAYABLE_EVENTS
+        ))
+        .bind(replay.webhook_id)
+        .bind(replay.from_time)
+        .bind(replay.to_time)
+        .bind(&replay.event_types)
+        .bind(replay.failed_only)
+        .bind(replay.cursor_created_at)
+        .bind(replay.cursor_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to get replayable events: {}", e)))
+    }
+
+    async fn record_delivery(
+        &self,
+        replay: &WebhookReplay,
+        event: &ReplayableEvent,
+        payload: &serde_json::Value,
+        delivery: &ReplayDelivery,
+    ) -> Result<WebhookReplay> {
+        let mut tx = self.db.begin().await
+            .map_err(|e| Error::Other(format!("Failed to start transaction: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (webhook_id, event_type, payload, response_status, response_body, error_message, delivered_at, replay_id, original_delivery_id)
+            VALUES ($1, $2, $3, $4, $5, $6, CASE WHEN $4::int BETWEEN 200 AND 299 THEN NOW() END, $7, $8)
+            "#
+        )
+        .bind(replay.webhook_id)
+        .bind(&event.event_type)
+        .bind(payload)
+        .bind(delivery.status)
+        .bind(&delivery.body)
+        .bind(&delivery.error)
+        .bind(replay.id)
+        .bind(event.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record replayed delivery: {}", e)))?;
+
+        let succeeded = delivery.is_success();
+        let replay = sqlx::query_as::<_, WebhookReplay>(
+            r#"
+            UPDATE webhook_replays
+            SET sent = sent + 1,
+                succeeded = succeeded + $2::int,
+                failed = failed + (1 - $2::int),
+                cursor_created_at = $3,
+                cursor_id = $4
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(replay.id)
+        .bind(succeeded as i32)
+        .bind(event.created_at)
+        .bind(event.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update webhook replay: {}", e)))?;
+
+        if succeeded {
+            sqlx::query("UPDATE webhooks SET last_triggered_at = NOW() WHERE id = $1")
+                .bind(replay.webhook_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to update webhook: {}", e)))?;
+        }
+
+        tx.commit().await
+            .map_err(|e| Error::Other(format!("Failed to commit transaction: {}", e)))?;
+
+        Ok(replay)
+    }
+
+    async fn set_status(&self, id: Uuid, status: WebhookReplayStatus, error: Option<&str>) -> Result<Option<WebhookReplay>> {
+        sqlx::query_as::<_, WebhookReplay>(
+            r#"
+            UPDATE webhook_replays
+            SET status = $2,
+                error_message = COALESCE($3, error_message),
+                started_at = CASE WHEN $2 = 'running' THEN COALESCE(started_at, NOW()) ELSE started_at END,
+                finished_at = CASE WHEN $2 IN ('completed', 'failed', 'cancelled') THEN NOW() ELSE finished_at END
+            WHERE id = $1 AND status IN ('pending', 'running')
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(status)
+        .bind(error)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to update webhook replay: {}", e)))
+    }
+}
@@ -0,0 +1,404 @@
+//! Envelope encryption for secrets stored in the database
+//!
+//! Every value is encrypted with its own random data key (AES-256-GCM) and
+//! the data key is encrypted ("wrapped") with the master key. A sealed value
+//! looks like
+//!
+//! ```text
+//! enc:v1:<master key id>:<wrapped data key>:<ciphertext>
+//! ```
+//!
+//! so a database dump or backup is useless without the master key. Rotating
+//! the master key only rewraps the data keys; values sealed under a previous
+//! key stay readable until they are rewrapped. Values without the `enc:`
+//! prefix are legacy plain text and are returned as they are.
+
+use std::{fmt, path::Path, sync::Arc};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::{SecretsConfig, SecretsProvider};
+use crate::{Error, Result};
+
+/// Prefix of sealed values
+pub const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Master and data key length (AES-256)
+const KEY_LEN: usize = 32;
+
+/// Associated data for the encrypted value itself
+const VALUE_AAD: &[u8] = b"rcommerce-secret-v1";
+
+/// Whether `value` is sealed (as opposed to legacy plain text)
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// A master key and its ID (a short hash, stored with each sealed value)
+#[derive(Clone)]
+pub struct MasterKey {
+    id: String,
+    key: [u8; KEY_LEN],
+}
+
+impl MasterKey {
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> Self {
+        let id = hex::encode(&Sha256::digest(key)[..4]);
+        Self { id, key }
+    }
+
+    /// Parse a base64-encoded 32-byte key
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| Error::Config(format!("Master key is not valid base64: {}", e)))?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| Error::Config(format!("Master key must be {} bytes", KEY_LEN)))?;
+        Ok(Self::from_bytes(key))
+    }
+
+    /// Generate a new base64-encoded master key
+    pub fn generate() -> Result<String> {
+        let mut key = [0u8; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| Error::Other("Failed to generate master key".to_string()))?;
+        Ok(STANDARD.encode(key))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn aead_key(&self) -> LessSafeKey {
+        aead_key(&self.key)
+    }
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    // AES-256-GCM accepts any 32-byte key
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("32-byte AES-256-GCM key"))
+}
+
+fn random<const N: usize>(rng: &SystemRandom) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    rng.fill(&mut bytes)
+        .map_err(|_| Error::Other("Failed to generate random bytes".to_string()))?;
+    Ok(bytes)
+}
+
+/// Encrypt `plaintext`, returning nonce || ciphertext || tag
+fn encrypt(key: &LessSafeKey, aad: &[u8], plaintext: &[u8], rng: &SystemRandom) -> Result<Vec<u8>> {
+    let nonce = random::<NONCE_LEN>(rng)?;
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut in_out)
+        .map_err(|_| Error::Other("Failed to encrypt secret".to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&in_out);
+    Ok(sealed)
+}
+
+/// Decrypt nonce || ciphertext || tag
+fn decrypt(key: &LessSafeKey, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(Error::Other("Sealed secret is truncated".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| Error::Other("Sealed secret has an invalid nonce".to_string()))?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(aad), &mut in_out)
+        .map_err(|_| Error::Other("Failed to decrypt secret (wrong key or corrupted value)".to_string()))?;
+    Ok(plaintext.to_vec())
+}
+
+/// The parts of a sealed value
+struct Envelope<'a> {
+    key_id: &'a str,
+    wrapped_key: Vec<u8>,
+    ciphertext: &'a str,
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(value: &'a str) -> Result<Self> {
+        let rest = value
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| Error::Other("Secret is not sealed".to_string()))?;
+        let mut parts = rest.splitn(3, ':');
+        let (Some(key_id), Some(wrapped_key), Some(ciphertext)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(Error::Other("Sealed secret is malformed".to_string()));
+        };
+        let wrapped_key = STANDARD
+            .decode(wrapped_key)
+            .map_err(|_| Error::Other("Sealed secret is malformed".to_string()))?;
+        Ok(Self { key_id, wrapped_key, ciphertext })
+    }
+}
+
+/// Master keys: the current one seals, previous ones are still accepted for reading
+struct Keyring {
+    current: MasterKey,
+    previous: Vec<MasterKey>,
+}
+
+impl Keyring {
+    fn find(&self, id: &str) -> Option<&MasterKey> {
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == id)
+    }
+}
+
+/// Seals and opens stored secrets
+///
+/// Cheap to clone. Without a master key (`SecretBox::disabled()`) values are
+/// stored in plain text and only plain-text values can be read.
+#[derive(Clone)]
+pub struct SecretBox {
+    keyring: Option<Arc<Keyring>>,
+    rng: SystemRandom,
+}
+
+impl Default for SecretBox {
+    fn default() -> Self {
+        Self { keyring: None, rng: SystemRandom::new() }
+    }
+}
+
+impl fmt::Debug for SecretBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretBox").field("key_id", &self.current_key_id()).finish()
+    }
+}
+
+impl SecretBox {
+    /// A box without a master key
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn new(current: MasterKey, previous: Vec<MasterKey>) -> Self {
+        Self {
+            keyring: Some(Arc::new(Keyring { current, previous })),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Load the master keys from the configured provider
+    pub fn from_config(config: &SecretsConfig) -> Result<Self> {
+        let (current, previous) = match config.provider {
+            SecretsProvider::Env => {
+                let current = std::env::var(&config.master_key_env).ok().filter(|k| !k.trim().is_empty());
+                let previous = std::env::var(&config.previous_master_keys_env)
+                    .map(|keys| keys.split(',').map(str::to_string).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                (current, previous)
+            }
+            SecretsProvider::File => {
+                let current = config.master_key_file.as_deref().map(read_key_file).transpose()?;
+                let previous = config
+                    .previous_master_key_files
+                    .iter()
+                    .map(|path| read_key_file(path))
+                    .collect::<Result<Vec<_>>>()?;
+                (current, previous)
+            }
+        };
+
+        let Some(current) = current else {
+            if config.require_master_key {
+                return Err(Error::Config("secrets.require_master_key is set but no master key is configured".to_string()));
+            }
+            return Ok(Self::disabled());
+        };
+        let previous = previous
+            .iter()
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| MasterKey::from_base64(key))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(MasterKey::from_base64(&current)?, previous))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.keyring.is_some()
+    }
+
+    /// ID of the master key new values are sealed with
+    pub fn current_key_id(&self) -> Option<&str> {
+        self.keyring.as_ref().map(|keyring| keyring.current.id())
+    }
+
+    /// Seal a secret for storage; returned unchanged without a master key
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let Some(keyring) = &self.keyring else {
+            return Ok(plaintext.to_string());
+        };
+        let data_key = random::<KEY_LEN>(&self.rng)?;
+        let ciphertext = encrypt(&aead_key(&data_key), VALUE_AAD, plaintext.as_bytes(), &self.rng)?;
+        let wrapped_key = self.wrap(&keyring.current, &data_key)?;
+        Ok(format!(
+            "{}{}:{}:{}",
+            SEALED_PREFIX,
+            keyring.current.id(),
+            STANDARD.encode(wrapped_key),
+            STANDARD.encode(ciphertext)
+        ))
+    }
+
+    /// Read a stored secret, sealed or legacy plain text
+    pub fn open(&self, stored: &str) -> Result<String> {
+        if !is_sealed(stored) {
+            return Ok(stored.to_string());
+        }
+        let envelope = Envelope::parse(stored)?;
+        let data_key = self.unwrap_key(&envelope)?;
+        let ciphertext = STANDARD
+            .decode(envelope.ciphertext)
+            .map_err(|_| Error::Other("Sealed secret is malformed".to_string()))?;
+        let plaintext = decrypt(&aead_key(&data_key), VALUE_AAD, &ciphertext)?;
+        String::from_utf8(plaintext).map_err(|_| Error::Other("Decrypted secret is not UTF-8".to_string()))
+    }
+
+    /// Whether a stored value is plain text or sealed under a previous master key
+    pub fn needs_reseal(&self, stored: &str) -> bool {
+        let Some(keyring) = &self.keyring else {
+            return false;
+        };
+        match Envelope::parse(stored) {
+            Ok(envelope) => envelope.key_id != keyring.current.id(),
+            Err(_) => !is_sealed(stored),
+        }
+    }
+
+    /// Bring a stored value up to date: seal plain text, rewrap the data key
+    /// of values sealed under a previous master key
+    pub fn reseal(&self, stored: &str) -> Result<String> {
+        let Some(keyring) = &self.keyring else {
+            return Ok(stored.to_string());
+        };
+        if !is_sealed(stored) {
+            return self.seal(stored);
+        }
+        let envelope = Envelope::parse(stored)?;
+        if envelope.key_id == keyring.current.id() {
+            return Ok(stored.to_string());
+        }
+        let data_key = self.unwrap_key(&envelope)?;
+        let wrapped_key = self.wrap(&keyring.current, &data_key)?;
+        Ok(format!(
+            "{}{}:{}:{}",
+            SEALED_PREFIX,
+            keyring.current.id(),
+            STANDARD.encode(wrapped_key),
+            envelope.ciphertext
+        ))
+    }
+
+    fn wrap(&self, master: &MasterKey, data_key: &[u8; KEY_LEN]) -> Result<Vec<u8>> {
+        encrypt(&master.aead_key(), master.id().as_bytes(), data_key, &self.rng)
+    }
+
+    fn unwrap_key(&self, envelope: &Envelope<'_>) -> Result<[u8; KEY_LEN]> {
+        let keyring = self
+            .keyring
+            .as_ref()
+            .ok_or_else(|| Error::Config("A sealed secret was found but no master key is configured".to_string()))?;
+        let master = keyring.find(envelope.key_id).ok_or_else(|| {
+            Error::Config(format!("Secret is sealed with unknown master key '{}'", envelope.key_id))
+        })?;
+        let data_key = decrypt(&master.aead_key(), master.id().as_bytes(), &envelope.wrapped_key)?;
+        data_key
+            .try_into()
+            .map_err(|_| Error::Other("Sealed secret has an invalid data key".to_string()))
+    }
+}
+
+fn read_key_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| Error::Config(format!("Failed to read master key file {:?}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> MasterKey {
+        MasterKey::from_bytes([byte; KEY_LEN])
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let secrets = SecretBox::new(key(1), Vec::new());
+        let sealed = secrets.seal("whsec_abc123").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("whsec_abc123"));
+        assert_eq!(secrets.open(&sealed).unwrap(), "whsec_abc123");
+        // Each value gets its own data key and nonce
+        assert_ne!(sealed, secrets.seal("whsec_abc123").unwrap());
+
+        // Legacy plain text is still readable
+        assert_eq!(secrets.open("plain").unwrap(), "plain");
+        assert!(secrets.needs_reseal("plain"));
+        assert!(!secrets.needs_reseal(&sealed));
+
+        // Wrong master key
+        assert!(SecretBox::new(key(2), Vec::new()).open(&sealed).is_err());
+        assert!(SecretBox::disabled().open(&sealed).is_err());
+
+        // Tampering is detected
+        let mut tampered = sealed.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(secrets.open(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_rotation_rewraps() {
+        let old = SecretBox::new(key(1), Vec::new());
+        let sealed = old.seal("s3cret").unwrap();
+
+        let rotated = SecretBox::new(key(2), vec![key(1)]);
+        assert_eq!(rotated.open(&sealed).unwrap(), "s3cret");
+        assert!(rotated.needs_reseal(&sealed));
+
+        let resealed = rotated.reseal(&sealed).unwrap();
+        assert!(!rotated.needs_reseal(&resealed));
+        assert!(resealed.starts_with(&format!("{}{}:", SEALED_PREFIX, key(2).id())));
+        // Only the data key is rewrapped
+        assert_eq!(resealed.rsplit(':').next(), sealed.rsplit(':').next());
+        assert_eq!(SecretBox::new(key(2), Vec::new()).open(&resealed).unwrap(), "s3cret");
+
+        assert!(is_sealed(&rotated.reseal("plain").unwrap()));
+    }
+
+    #[test]
+    fn test_disabled_box_passes_through() {
+        let secrets = SecretBox::disabled();
+        assert_eq!(secrets.seal("plain").unwrap(), "plain");
+        assert!(!secrets.needs_reseal("plain"));
+        assert_eq!(secrets.current_key_id(), None);
+    }
+
+    #[test]
+    fn test_master_key_parsing() {
+        let encoded = MasterKey::generate().unwrap();
+        let key = MasterKey::from_base64(&encoded).unwrap();
+        assert_eq!(key.id().len(), 8);
+        assert!(!format!("{:?}", key).contains(&encoded));
+        assert!(MasterKey::from_base64("c2hvcnQ=").is_err());
+        assert!(MasterKey::from_base64("not base64!").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Access Log Service
+//!
+//! Requests are buffered in memory and written in batches, so logging never
+//! waits on the database. When the buffer is full (the database is down or
+//! too slow) further requests go unlogged and are counted as dropped.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+
+use crate::config::AccessLogConfig;
+use crate::jobs::recurring::RecurringJob;
+use crate::models::{AccessLogEntry, AccessLogFilter, NewAccessLogEntry};
+use crate::repository::AccessLogRepository;
+use crate::Result;
+
+/// Entries returned by one search at most
+pub const MAX_SEARCH_LIMIT: i64 = 1000;
+
+/// Entries written per insert
+const WRITE_BATCH_SIZE: usize = 1000;
+
+/// Access log service
+pub struct AccessLogService<R: AccessLogRepository> {
+    repository: R,
+    config: AccessLogConfig,
+    buffer: Mutex<Vec<NewAccessLogEntry>>,
+    dropped: AtomicU64,
+}
+
+impl<R: AccessLogRepository> AccessLogService<R> {
+    pub fn new(repository: R, config: AccessLogConfig) -> Self {
+        Self {
+            repository,
+            config,
+            buffer: Mutex::new(Vec::new()),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &AccessLogConfig {
+        &self.config
+    }
+
+    /// Whether a request path is logged
+    pub fn is_logged(&self, path: &str) -> bool {
+        self.config.enabled && !self.config.exclude_paths.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    /// Queue an entry for the next write
+    pub fn record(&self, entry: NewAccessLogEntry) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.len() >= self.config.buffer_size {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        buffer.push(entry);
+    }
+
+    /// Write the buffered entries; returns how many
+    pub async fn flush(&self) -> Result<usize> {
+        let entries = std::mem::take(&mut *self.buffer.lock().unwrap());
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::warn!("Access log buffer was full; {} requests went unlogged", dropped);
+        }
+        for batch in entries.chunks(WRITE_BATCH_SIZE) {
+            self.repository.insert_batch(batch).await?;
+        }
+        Ok(entries.len())
+    }
+
+    /// Matching entries, newest first
+    pub async fn search(&self, filter: &AccessLogFilter, limit: i64) -> Result<Vec<AccessLogEntry>> {
+        self.repository.search(filter, limit.clamp(1, MAX_SEARCH_LIMIT)).await
+    }
+
+    /// Delete entries older than `retention_days`; returns how many
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let cutoff = Utc::now() - Duration::days(i64::from(self.config.retention_days));
+        self.repository.purge_before(cutoff).await
+    }
+}
+
+/// The `access_log_retention` recurring job
+pub struct AccessLogRetentionJob<R: AccessLogRepository> {
+    access_log: Arc<AccessLogService<R>>,
+}
+
+impl<R: AccessLogRepository> AccessLogRetentionJob<R> {
+    pub fn new(access_log: Arc<AccessLogService<R>>) -> Self {
+        Self { access_log }
+    }
+}
+
+#[async_trait]
+impl<R: AccessLogRepository + 'static> RecurringJob for AccessLogRetentionJob<R> {
+    fn name(&self) -> &str {
+        "access_log_retention"
+    }
+
+    fn description(&self) -> &str {
+        "Delete access log entries past their retention period"
+    }
+
+    fn default_schedule(&self) -> String {
+        "15 * * * *".to_string()
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(format!("purged {} entries", self.access_log.purge_expired().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[derive(Default)]
+    struct MockRepository {
+        written: Mutex<Vec<NewAccessLogEntry>>,
+    }
+
+    #[async_trait]
+    impl AccessLogRepository for MockRepository {
+        async fn insert_batch(&self, entries: &[NewAccessLogEntry]) -> Result<()> {
+            self.written.lock().unwrap().extend_from_slice(entries);
+            Ok(())
+        }
+
+        async fn search(&self, _filter: &AccessLogFilter, _limit: i64) -> Result<Vec<AccessLogEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn purge_before(&self, _before: DateTime<Utc>) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn entry(path: &str) -> NewAccessLogEntry {
+        NewAccessLogEntry {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            status: 200,
+            latency_ms: 3,
+            api_key_prefix: Some("ak_partner".to_string()),
+            client_network: Some("203.0.113.0".to_string()),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_drops_entries() {
+        let config = AccessLogConfig { enabled: true, buffer_size: 2, ..Default::default() };
+        let service = AccessLogService::new(MockRepository::default(), config);
+        for _ in 0..3 {
+            service.record(entry("/api/v1/products"));
+        }
+        assert_eq!(service.flush().await.unwrap(), 2);
+        assert_eq!(service.flush().await.unwrap(), 0);
+        assert_eq!(service.repository.written.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_excluded_paths() {
+        let config = AccessLogConfig { enabled: true, ..Default::default() };
+        let service = AccessLogService::new(MockRepository::default(), config);
+        assert!(service.is_logged("/api/v1/orders"));
+        assert!(!service.is_logged("/health"));
+        assert!(!AccessLogService::new(MockRepository::default(), AccessLogConfig::default()).is_logged("/api/v1/orders"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Account Service
+//!
+//! Self-service for signed-in customers: their order history with
+//! shipment tracking, the lines to reorder, payment methods saved through
+//! the gateways' tokenization APIs, notification preferences, and closing
+//! the account. Closed accounts are soft deleted, so staff can restore
+//! them until the soft delete purge removes them.
+
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::models::{
+    AccountClosure, AccountOrder, AccountOrderDetail, AccountOrderLine, AddPaymentMethodRequest,
+    CustomerNotificationPreferences, SavedPaymentMethod, UpdateNotificationPreferences,
+};
+use crate::notification::NotificationCategory;
+use crate::payment::agnostic::PaymentService;
+use crate::repository::AccountRepository;
+use crate::{Error, Result};
+
+/// Customer account service
+pub struct AccountService<R: AccountRepository> {
+    repository: R,
+    payments: Arc<PaymentService>,
+}
+
+impl<R: AccountRepository> AccountService<R> {
+    pub fn new(repository: R, payments: Arc<PaymentService>) -> Self {
+        Self { repository, payments }
+    }
+
+    pub async fn orders(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AccountOrder>> {
+        self.repository.orders(customer_id, limit, offset).await
+    }
+
+    /// One of the customer's orders with its lines and shipments
+    pub async fn order(&self, customer_id: Uuid, order_id: Uuid) -> Result<AccountOrderDetail> {
+        let order = self
+            .repository
+            .find_order(customer_id, order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+        Ok(AccountOrderDetail {
+            items: self.repository.order_lines(order_id).await?,
+            shipments: self.repository.order_shipments(order_id).await?,
+            order,
+        })
+    }
+
+    /// Lines of an order to add to the cart again; bundles come back whole
+    pub async fn reorder_lines(&self, customer_id: Uuid, order_id: Uuid) -> Result<Vec<AccountOrderLine>> {
+        if self.repository.find_order(customer_id, order_id).await?.is_none() {
+            return Err(Error::not_found("Order not found"));
+        }
+        let lines = self.repository.order_lines(order_id).await?;
+        Ok(lines.into_iter().filter(|line| !line.is_bundle_component).collect())
+    }
+
+    pub async fn notification_preferences(&self, customer_id: Uuid) -> Result<CustomerNotificationPreferences> {
+        Ok(self
+            .repository
+            .notification_preferences(customer_id)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn update_notification_preferences(
+        &self,
+        customer_id: Uuid,
+        update: &UpdateNotificationPreferences,
+    ) -> Result<CustomerNotificationPreferences> {
+        let current = self.notification_preferences(customer_id).await?;
+        let preferences = update.apply(&current)?;
+        self.repository.save_notification_preferences(customer_id, &preferences).await?;
+        Ok(preferences)
+    }
+
+    /// Stop one category of notifications, from an unsubscribe link
+    pub async fn unsubscribe(
+        &self,
+        customer_id: Uuid,
+        category: NotificationCategory,
+    ) -> Result<CustomerNotificationPreferences> {
+        self.update_notification_preferences(customer_id, &category.opt_out()?).await
+    }
+
+    pub async fn payment_methods(&self, customer_id: Uuid) -> Result<Vec<SavedPaymentMethod>> {
+        self.repository.payment_methods(customer_id).await
+    }
+
+    /// Tokenize a payment method with the gateway and save it for the customer
+    pub async fn add_payment_method(
+        &self,
+        customer_id: Uuid,
+        request: AddPaymentMethodRequest,
+    ) -> Result<SavedPaymentMethod> {
+        let gateway_id = request
+            .gateway_id
+            .unwrap_or_else(|| self.payments.default_gateway().to_string());
+        let gateway = self
+            .payments
+            .get_gateway(Some(&gateway_id))
+            .ok_or_else(|| Error::validation(format!("Gateway '{}' not found", gateway_id)))?;
+        let token = gateway.tokenize_payment_method(request.payment_method_data).await?;
+        self.repository
+            .save_payment_method(customer_id, &gateway_id, &token, request.make_default)
+            .await
+    }
+
+    /// Remove a saved payment method at the gateway and forget it
+    pub async fn remove_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<()> {
+        let method = self
+            .repository
+            .find_payment_method(customer_id, id)
+            .await?
+            .ok_or_else(|| Error::not_found("Payment method not found"))?;
+        self.detach(&method).await?;
+        self.repository.delete_payment_method(customer_id, id).await?;
+        Ok(())
+    }
+
+    pub async fn set_default_payment_method(&self, customer_id: Uuid, id: Uuid) -> Result<SavedPaymentMethod> {
+        if !self.repository.set_default_payment_method(customer_id, id).await? {
+            return Err(Error::not_found("Payment method not found"));
+        }
+        self.repository
+            .find_payment_method(customer_id, id)
+            .await?
+            .ok_or_else(|| Error::not_found("Payment method not found"))
+    }
+
+    /// Close an account: its payment methods are removed at the gateways,
+    /// it is opted out of all notifications and soft deleted. Accounts with
+    /// orders in progress or live subscriptions stay open.
+    pub async fn close(&self, customer_id: Uuid) -> Result<AccountClosure> {
+        let (open_orders, subscriptions) = self.repository.open_commitments(customer_id).await?;
+        check_closable(open_orders, subscriptions)?;
+
+        let methods = self.repository.payment_methods(customer_id).await?;
+        for method in &methods {
+            // The account closes regardless; the gateway keeps an orphaned token at worst
+            if let Err(e) = self.detach(method).await {
+                tracing::warn!("Failed to remove payment method {} of closed account {}: {}", method.id, customer_id, e);
+            }
+        }
+        if !self.repository.close(customer_id).await? {
+            return Err(Error::not_found("Customer not found"));
+        }
+        Ok(AccountClosure {
+            customer_id,
+            payment_methods_removed: methods.len(),
+            sessions_revoked: 0,
+        })
+    }
+
+    async fn detach(&self, method: &SavedPaymentMethod) -> Result<()> {
+        let gateway = self
+            .payments
+            .get_gateway(Some(&method.gateway))
+            .ok_or_else(|| Error::payment_error(format!("Payment gateway '{}' not configured", method.gateway)))?;
+        gateway.delete_payment_method(&method.token).await
+    }
+}
+
+/// Refuse to close accounts with something still to deliver or bill
+fn check_closable(open_orders: i64, subscriptions: i64) -> Result<()> {
+    if open_orders > 0 {
+        return Err(Error::validation(format!(
+            "The account has {} order(s) in progress; close it once they are completed or cancelled",
+            open_orders
+        )));
+    }
+    if subscriptions > 0 {
+        return Err(Error::validation(format!(
+            "The account has {} live subscription(s); cancel them before closing it",
+            subscriptions
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_closable() {
+        assert!(check_closable(0, 0).is_ok());
+        assert!(check_closable(1, 0).is_err());
+        assert!(check_closable(0, 2).is_err());
+    }
+}
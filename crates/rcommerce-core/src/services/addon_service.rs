@@ -0,0 +1,139 @@
+//! Add-on Service
+//!
+//! Manages the add-on catalog (gift wrap, gift messages, assembly) and the
+//! add-ons shoppers choose on their cart before checkout. Checkout prices
+//! and taxes chosen add-ons and records them on the order; the order's pick
+//! list carries their fulfillment instructions to the warehouse.
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    AddCartAddonRequest, Addon, AddonScope, CartAddon, CreateAddonRequest, OrderAddon, PickList, UpdateAddonRequest,
+};
+use crate::repository::AddonRepository;
+use crate::{Error, Result};
+
+/// Add-on service
+pub struct AddonService<R: AddonRepository> {
+    repository: R,
+}
+
+impl<R: AddonRepository> AddonService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create an add-on
+    pub async fn create_addon(&self, mut request: CreateAddonRequest) -> Result<Addon> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        request.code = request.code.trim().to_lowercase();
+        if request.code.is_empty() || request.name.trim().is_empty() {
+            return Err(Error::validation("Add-on code and name must not be empty"));
+        }
+        if request.price < Decimal::ZERO {
+            return Err(Error::validation("Add-on price cannot be negative"));
+        }
+        if request.per_unit && request.scope == AddonScope::Order {
+            return Err(Error::validation("Only item add-ons can be charged per unit"));
+        }
+        self.repository.create(&request).await
+    }
+
+    /// Update an add-on
+    pub async fn update_addon(&self, id: Uuid, request: UpdateAddonRequest) -> Result<Addon> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.price.is_some_and(|price| price < Decimal::ZERO) {
+            return Err(Error::validation("Add-on price cannot be negative"));
+        }
+        if request.per_unit == Some(true) && self.get_addon(id).await?.scope == AddonScope::Order {
+            return Err(Error::validation("Only item add-ons can be charged per unit"));
+        }
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Add-on not found"))
+    }
+
+    /// Get an add-on
+    pub async fn get_addon(&self, id: Uuid) -> Result<Addon> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Add-on not found"))
+    }
+
+    /// Add-ons by sort order; storefronts only see active ones
+    pub async fn list_addons(&self, active_only: bool) -> Result<Vec<Addon>> {
+        self.repository.list(active_only).await
+    }
+
+    /// Add-ons chosen on a cart
+    pub async fn cart_addons(&self, cart_id: Uuid) -> Result<Vec<CartAddon>> {
+        self.repository.cart_addons(cart_id).await
+    }
+
+    /// Choose an add-on for a cart (order add-ons) or one of its lines
+    /// (item add-ons); choosing it again replaces the message
+    pub async fn add_to_cart(&self, cart_id: Uuid, mut request: AddCartAddonRequest) -> Result<Vec<CartAddon>> {
+        let addon = self.get_addon(request.addon_id).await?;
+        if !addon.is_active {
+            return Err(Error::validation(format!("{} is not available", addon.name)));
+        }
+
+        match (addon.scope, request.cart_item_id) {
+            (AddonScope::Order, Some(_)) => {
+                return Err(Error::validation(format!("{} applies to the whole order, not a cart item", addon.name)));
+            }
+            (AddonScope::Item, None) => {
+                return Err(Error::validation(format!("{} must be added to a cart item", addon.name)));
+            }
+            (AddonScope::Item, Some(cart_item_id)) => {
+                if self.repository.cart_item_quantity(cart_id, cart_item_id).await?.is_none() {
+                    return Err(Error::not_found("Cart item not found"));
+                }
+            }
+            (AddonScope::Order, None) => {}
+        }
+
+        request.message = request
+            .message
+            .map(|message| message.trim().to_string())
+            .filter(|message| !message.is_empty());
+        addon.check_message(request.message.as_deref()).map_err(Error::validation)?;
+
+        self.repository.add_to_cart(cart_id, &request).await?;
+        self.repository.cart_addons(cart_id).await
+    }
+
+    /// Remove an add-on from a cart
+    pub async fn remove_from_cart(&self, cart_id: Uuid, cart_addon_id: Uuid) -> Result<()> {
+        if !self.repository.remove_from_cart(cart_id, cart_addon_id).await? {
+            return Err(Error::not_found("Add-on not found on this cart"));
+        }
+        Ok(())
+    }
+
+    /// Add-ons sold on an order
+    pub async fn order_addons(&self, order_id: Uuid) -> Result<Vec<OrderAddon>> {
+        self.repository.order_addons(order_id).await
+    }
+
+    /// An order's pick list: the lines to ship with their add-on
+    /// instructions, then the order-level ones
+    pub async fn pick_list(&self, order_id: Uuid) -> Result<PickList> {
+        let order_number = self
+            .repository
+            .order_number(order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+        let items = self.repository.pick_items(order_id).await?;
+        let addons = self.repository.order_addons(order_id).await?;
+        Ok(PickList::build(order_id, order_number, items, &addons))
+    }
+}
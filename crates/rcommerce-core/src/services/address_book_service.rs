@@ -0,0 +1,219 @@
+//! Address Book Service
+//!
+//! Customers keep labelled shipping and billing addresses, with at most one
+//! default of each kind. New and moved addresses are checked with a shipping
+//! carrier's address validation: an address the carrier rejects is refused,
+//! a corrected one is saved as corrected, and if the carrier cannot be
+//! reached the address is saved unvalidated.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{CreateAddressRequest, CustomerAddress, UpdateAddressRequest};
+use crate::repository::{AddressCheck, AddressRepository};
+use crate::shipping::{ShippingProvider, ShippingProviderFactory};
+use crate::{Error, Result};
+
+/// Address book service
+pub struct AddressBookService<R: AddressRepository> {
+    repository: R,
+    shipping: Option<Arc<ShippingProviderFactory>>,
+    /// Preferred carrier for validation (`[shipping] default_provider`)
+    provider: Option<String>,
+}
+
+impl<R: AddressRepository> AddressBookService<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            shipping: None,
+            provider: None,
+        }
+    }
+
+    /// Validate addresses with a shipping carrier, preferring `provider`
+    pub fn with_validation(mut self, shipping: Arc<ShippingProviderFactory>, provider: Option<String>) -> Self {
+        self.shipping = Some(shipping);
+        self.provider = provider;
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// A customer's addresses, defaults first
+    pub async fn list(&self, customer_id: Uuid) -> Result<Vec<CustomerAddress>> {
+        self.require_customer(customer_id).await?;
+        self.repository.list(customer_id).await
+    }
+
+    /// Get one of a customer's addresses
+    pub async fn get(&self, customer_id: Uuid, id: Uuid) -> Result<CustomerAddress> {
+        self.repository
+            .find(customer_id, id)
+            .await?
+            .ok_or_else(|| Error::not_found("Address not found"))
+    }
+
+    /// Add an address to a customer's address book
+    pub async fn create(&self, customer_id: Uuid, request: CreateAddressRequest) -> Result<CustomerAddress> {
+        self.require_customer(customer_id).await?;
+        let mut request = normalize(request)?;
+        let check = self.validate_with_carrier(customer_id, &mut request).await?;
+        self.repository.create(customer_id, &request, &check).await
+    }
+
+    /// Change an address; it is validated again if its location changed
+    pub async fn update(&self, customer_id: Uuid, id: Uuid, request: UpdateAddressRequest) -> Result<CustomerAddress> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let current = self.get(customer_id, id).await?;
+        let moved = request.changes_location();
+        let mut request = normalize(request.apply(&current))?;
+
+        let check = if moved {
+            self.validate_with_carrier(customer_id, &mut request).await?
+        } else {
+            AddressCheck {
+                provider: current.validation_provider.clone(),
+                residential: current.residential,
+                validated_at: current.validated_at,
+            }
+        };
+
+        self.repository
+            .update(customer_id, id, &request, &check)
+            .await?
+            .ok_or_else(|| Error::not_found("Address not found"))
+    }
+
+    /// Remove an address from a customer's address book
+    pub async fn delete(&self, customer_id: Uuid, id: Uuid) -> Result<()> {
+        if !self.repository.delete(customer_id, id).await? {
+            return Err(Error::not_found("Address not found"));
+        }
+        Ok(())
+    }
+
+    async fn require_customer(&self, customer_id: Uuid) -> Result<()> {
+        if !self.repository.customer_exists(customer_id).await? {
+            return Err(Error::not_found("Customer not found"));
+        }
+        Ok(())
+    }
+
+    /// The configured carrier if it is available, otherwise the first
+    /// available one
+    fn validation_provider(&self) -> Option<&dyn ShippingProvider> {
+        let shipping = self.shipping.as_ref()?;
+        if let Some(preferred) = self.provider.as_deref() {
+            if let Ok(provider) = shipping.get(preferred) {
+                if provider.is_available() {
+                    return Some(provider);
+                }
+            }
+        }
+        shipping.get_available().into_iter().min_by_key(|p| p.id())
+    }
+
+    /// Check the address with a carrier, applying its corrections
+    async fn validate_with_carrier(
+        &self,
+        customer_id: Uuid,
+        request: &mut CreateAddressRequest,
+    ) -> Result<AddressCheck> {
+        let Some(provider) = self.validation_provider() else {
+            return Ok(AddressCheck::default());
+        };
+
+        let address = request.to_address(customer_id);
+        let validation = match provider.validate_address(&address).await {
+            Ok(validation) => validation,
+            Err(e) => {
+                tracing::warn!(
+                    "Address validation with {} failed, saving unvalidated: {}",
+                    provider.id(),
+                    e
+                );
+                return Ok(AddressCheck::default());
+            }
+        };
+
+        if !validation.is_valid {
+            let reason = if validation.messages.is_empty() {
+                "address not recognised by the carrier".to_string()
+            } else {
+                validation.messages.join("; ")
+            };
+            return Err(Error::validation(format!("Invalid address: {}", reason)));
+        }
+
+        if let Some(normalized) = validation.normalized_address {
+            request.address1 = normalized.address1;
+            request.address2 = normalized.address2.or(request.address2.take());
+            request.city = normalized.city;
+            request.state = normalized.state.or(request.state.take());
+            request.zip = normalized.zip;
+            request.country = normalized.country.trim().to_uppercase();
+        }
+
+        Ok(AddressCheck {
+            provider: Some(provider.id().to_string()),
+            residential: validation.residential,
+            validated_at: Some(Utc::now()),
+        })
+    }
+}
+
+/// Validate a request and tidy its fields
+fn normalize(mut request: CreateAddressRequest) -> Result<CreateAddressRequest> {
+    request.country = request.country.trim().to_uppercase();
+    request.label = request
+        .label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+    request.validate().map_err(|e| Error::validation(e.to_string()))?;
+    if request.address1.trim().is_empty() || request.city.trim().is_empty() {
+        return Err(Error::validation("Address line and city must not be empty"));
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> CreateAddressRequest {
+        CreateAddressRequest {
+            label: Some("  Home ".to_string()),
+            first_name: "Jane".to_string(),
+            last_name: "Doe".to_string(),
+            company: None,
+            phone: None,
+            address1: "1 Main St".to_string(),
+            address2: None,
+            city: "Springfield".to_string(),
+            state: Some("IL".to_string()),
+            country: " us".to_string(),
+            zip: "62701".to_string(),
+            is_default_shipping: true,
+            is_default_billing: false,
+        }
+    }
+
+    #[test]
+    fn test_normalize_address_request() {
+        let normalized = normalize(request()).unwrap();
+        assert_eq!(normalized.country, "US");
+        assert_eq!(normalized.label.as_deref(), Some("Home"));
+
+        let blank = CreateAddressRequest { city: "  ".to_string(), ..request() };
+        assert!(normalize(blank).is_err());
+
+        let bad_country = CreateAddressRequest { country: "USA".to_string(), ..request() };
+        assert!(normalize(bad_country).is_err());
+    }
+}
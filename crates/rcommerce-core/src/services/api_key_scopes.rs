@@ -243,6 +243,14 @@ impl ScopeChecker {
         self.can(resource, Action::Write)
     }
 
+    /// Check a staff permission: like `can`, but the global `read` and
+    /// `write` scopes every customer account carries don't count
+    pub fn can_staff(&self, resource: Resource, action: Action) -> bool {
+        self.scopes.iter().any(|scope| {
+            (scope.resource.is_some() || scope.action == Action::Admin) && scope.allows(resource, action)
+        })
+    }
+
     /// Check if has admin access
     pub fn is_admin(&self) -> bool {
         self.scopes.iter().any(|s| s.action == Action::Admin)
@@ -405,6 +413,22 @@ mod tests {
         assert!(checker.can_write(Resource::Orders));
     }
 
+    #[test]
+    fn test_staff_checker() {
+        let checker = ScopeChecker::new(&presets::read_write()).unwrap();
+        assert!(checker.can_write(Resource::Orders));
+        assert!(!checker.can_staff(Resource::Orders, Action::Read));
+
+        let scopes = vec!["read".to_string(), "orders:write".to_string()];
+        let checker = ScopeChecker::new(&scopes).unwrap();
+        assert!(checker.can_staff(Resource::Orders, Action::Write));
+        assert!(!checker.can_staff(Resource::Orders, Action::Admin));
+        assert!(!checker.can_staff(Resource::Reports, Action::Read));
+
+        let checker = ScopeChecker::new(&presets::admin()).unwrap();
+        assert!(checker.can_staff(Resource::Users, Action::Admin));
+    }
+
     #[test]
     fn test_presets() {
         let checker = ScopeChecker::new(&presets::read_only()).unwrap();
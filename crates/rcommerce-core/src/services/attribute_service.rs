@@ -0,0 +1,135 @@
+//! Attribute Service
+//!
+//! Manages the shop's product attributes and their options, sets the values
+//! of products and variants, and reads the `?attr=` filters of product
+//! listings (see `models::attribute`). Facet counts of a listing come from
+//! `ProductService::attribute_facets`.
+
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    check_attribute_input, parse_attribute_filter, resolve_attribute_values, AssignedAttribute, AttributeCondition,
+    AttributeType, AttributeWithOptions, CreateAttributeRequest, ProductAttributes, SetAttributeValuesRequest,
+    UpdateAttributeRequest,
+};
+use crate::repository::AttributeRepository;
+use crate::{Error, Result};
+
+/// Attribute service
+pub struct AttributeService<R: AttributeRepository> {
+    repository: R,
+}
+
+impl<R: AttributeRepository> AttributeService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create an attribute; filterable unless it is text
+    pub async fn create_attribute(&self, request: CreateAttributeRequest) -> Result<AttributeWithOptions> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        check_attribute_input(Some(request.code.trim()), request.attribute_type, &request.options)?;
+        let filterable = request.filterable.unwrap_or(request.attribute_type != AttributeType::Text);
+        check_filterable(request.attribute_type, filterable)?;
+        self.repository.create(&request, filterable).await
+    }
+
+    /// Update an attribute; new options replace its options
+    pub async fn update_attribute(&self, id: Uuid, request: UpdateAttributeRequest) -> Result<AttributeWithOptions> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let attribute_type = self.get_attribute(id).await?.attribute.attribute_type;
+        if let Some(options) = &request.options {
+            check_attribute_input(None, attribute_type, options)?;
+        }
+        check_filterable(attribute_type, request.filterable.unwrap_or(false))?;
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Attribute not found"))
+    }
+
+    /// Get an attribute with its options
+    pub async fn get_attribute(&self, id: Uuid) -> Result<AttributeWithOptions> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Attribute not found"))
+    }
+
+    /// Delete an attribute; products lose their values of it
+    pub async fn delete_attribute(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete(id).await? {
+            return Err(Error::not_found("Attribute not found"));
+        }
+        Ok(())
+    }
+
+    /// Attributes listings can be filtered by
+    pub async fn filterable(&self) -> Result<Vec<AttributeWithOptions>> {
+        let mut attributes = self.repository.list().await?;
+        attributes.retain(|a| a.attribute.filterable && a.attribute.attribute_type != AttributeType::Text);
+        Ok(attributes)
+    }
+
+    /// The conditions of an `?attr=` listing filter
+    pub async fn parse_filter(&self, spec: &str) -> Result<Vec<AttributeCondition>> {
+        if spec.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        parse_attribute_filter(spec, &self.filterable().await?)
+    }
+
+    /// Replace the values of a product, or of one of its variants; the
+    /// values as set
+    pub async fn set_values(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        request: SetAttributeValuesRequest,
+    ) -> Result<Vec<AssignedAttribute>> {
+        let schema = self.repository.list().await?;
+        let values = resolve_attribute_values(&schema, &request)?;
+        if !self.repository.set_values(product_id, variant_id, &values).await? {
+            return Err(Error::not_found("Product or variant not found"));
+        }
+        let attributes = ProductAttributes::new(&schema, &self.repository.values(product_id).await?);
+        Ok(match variant_id {
+            Some(variant_id) => attributes.variant(variant_id),
+            None => attributes.product,
+        })
+    }
+
+    /// The attribute values of a product and its variants
+    pub async fn product_attributes(&self, product_id: Uuid) -> Result<ProductAttributes> {
+        let values = self.repository.values(product_id).await?;
+        if values.is_empty() {
+            return Ok(ProductAttributes::default());
+        }
+        Ok(ProductAttributes::new(&self.repository.list().await?, &values))
+    }
+}
+
+fn check_filterable(attribute_type: AttributeType, filterable: bool) -> Result<()> {
+    if filterable && attribute_type == AttributeType::Text {
+        return Err(Error::validation("Text attributes can't be filterable"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_filterable() {
+        assert!(check_filterable(AttributeType::Select, true).is_ok());
+        assert!(check_filterable(AttributeType::Number, true).is_ok());
+        assert!(check_filterable(AttributeType::Text, false).is_ok());
+        assert!(check_filterable(AttributeType::Text, true).is_err());
+    }
+}
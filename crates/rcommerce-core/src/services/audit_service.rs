@@ -0,0 +1,353 @@
+//! Audit Service
+//!
+//! Works out which resource a mutating call touches from its path, snapshots
+//! that row before and after the call, and appends the changed fields to the
+//! audit log. Unlike the access log, entries are written as the call
+//! completes rather than buffered, so none are lost when the server stops.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::config::AuditConfig;
+use crate::jobs::recurring::RecurringJob;
+use crate::models::{diff_snapshots, is_credential_field, AuditEntry, AuditFilter, Cursor, CursorPage, NewAuditEntry};
+use crate::repository::AuditRepository;
+use crate::{Error, Result};
+
+/// Entries returned by one search at most
+pub const MAX_SEARCH_LIMIT: i64 = 1000;
+
+/// Replacement for credential values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Path segments naming resources whose rows are snapshotted, with their tables
+const SNAPSHOT_TABLES: &[(&str, &str)] = &[
+    ("products", "products"),
+    ("variants", "product_variants"),
+    ("orders", "orders"),
+    ("payments", "payments"),
+    ("returns", "returns"),
+    ("customers", "customers"),
+    ("customer-groups", "customer_groups"),
+    ("coupons", "coupons"),
+    ("categories", "product_categories"),
+    ("collections", "collections"),
+    ("gift-cards", "gift_cards"),
+    ("price-lists", "price_lists"),
+    ("flash-sales", "flash_sales"),
+    ("subscriptions", "subscriptions"),
+    ("subscription-plans", "subscription_plans"),
+    ("purchase-orders", "purchase_orders"),
+    ("suppliers", "suppliers"),
+    ("webhooks", "webhooks"),
+];
+
+/// What an audited call acts on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditTarget {
+    /// e.g. `products`
+    pub resource_type: String,
+    pub resource_id: Option<String>,
+    /// Snapshot table, for resources in the snapshot list
+    pub table: Option<&'static str>,
+    /// Set for creations: the new row's ID is taken from the response
+    pub created: bool,
+}
+
+impl AuditTarget {
+    /// Row to snapshot, once the ID is known
+    fn row(&self) -> Option<(&'static str, Uuid)> {
+        let id = self.resource_id.as_deref()?.parse().ok()?;
+        Some((self.table?, id))
+    }
+}
+
+fn snapshot_table(segment: &str) -> Option<&'static str> {
+    SNAPSHOT_TABLES.iter().find(|(name, _)| *name == segment).map(|(_, table)| *table)
+}
+
+/// The resource a call acts on, from its path: the innermost
+/// `<resource>/<uuid>` pair of a snapshotted resource (so
+/// `/products/:id/variants/:variant_id` is the variant), a creation when a
+/// POST ends at such a resource, or else the first segment and ID.
+pub fn audit_target(method: &str, path: &str) -> Option<AuditTarget> {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    let path = path.strip_prefix("/admin").unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let first = *segments.first()?;
+
+    if method == "POST" {
+        if let Some(table) = segments.last().and_then(|last| snapshot_table(last)) {
+            return Some(AuditTarget {
+                resource_type: segments[segments.len() - 1].to_string(),
+                resource_id: None,
+                table: Some(table),
+                created: true,
+            });
+        }
+    }
+
+    let innermost = segments.windows(2).rev().find_map(|pair| {
+        let table = snapshot_table(pair[0])?;
+        pair[1].parse::<Uuid>().ok()?;
+        Some(AuditTarget {
+            resource_type: pair[0].to_string(),
+            resource_id: Some(pair[1].to_string()),
+            table: Some(table),
+            created: false,
+        })
+    });
+    Some(innermost.unwrap_or_else(|| AuditTarget {
+        resource_type: first.to_string(),
+        resource_id: segments.get(1).filter(|s| s.parse::<Uuid>().is_ok()).map(|s| s.to_string()),
+        table: None,
+        created: false,
+    }))
+}
+
+/// ID of a created resource in a response body: `id`, or the `id` of an
+/// object the response wraps it in (e.g. `{"product": {"id": ...}}`)
+pub fn created_id(body: &Value) -> Option<String> {
+    let id = |value: &Value| value.get("id").and_then(Value::as_str).map(str::to_string);
+    id(body).or_else(|| body.as_object()?.values().filter(|v| v.is_object()).find_map(id))
+}
+
+/// Audit service
+pub struct AuditService<R: AuditRepository> {
+    repository: R,
+    config: AuditConfig,
+}
+
+impl<R: AuditRepository> AuditService<R> {
+    pub fn new(repository: R, config: AuditConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn config(&self) -> &AuditConfig {
+        &self.config
+    }
+
+    /// Whether a call is audited: mutating methods outside `exclude_paths`
+    pub fn is_audited(&self, method: &str, path: &str) -> bool {
+        self.config.enabled
+            && matches!(method, "POST" | "PUT" | "PATCH" | "DELETE")
+            && !self.config.exclude_paths.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    /// The target's row as it is now; None when it has no snapshot table,
+    /// doesn't exist, or can't be read (the call goes ahead regardless)
+    pub async fn snapshot(&self, target: &AuditTarget) -> Option<Value> {
+        let (table, id) = target.row()?;
+        match self.repository.snapshot(table, id).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!("Audit snapshot of {} {} failed: {}", table, id, e);
+                None
+            }
+        }
+    }
+
+    /// Append a call with the fields that changed between the snapshots
+    pub async fn record(&self, mut entry: NewAuditEntry, before: Option<Value>, after: Option<Value>) -> Result<()> {
+        entry.changes = diff_snapshots(before.as_ref(), after.as_ref()).map(|mut changes| {
+            self.redact_changes(&mut changes);
+            changes
+        });
+        self.repository.insert(&entry).await
+    }
+
+    fn is_redacted(&self, name: &str) -> bool {
+        is_credential_field(name) || self.config.redact_fields.iter().any(|f| f.eq_ignore_ascii_case(name))
+    }
+
+    /// Mask credential fields: the change is kept, its values aren't
+    fn redact_changes(&self, changes: &mut Value) {
+        let Value::Object(fields) = changes else { return };
+        for (name, change) in fields.iter_mut() {
+            if self.is_redacted(name) {
+                *change = serde_json::json!({ "before": REDACTED, "after": REDACTED });
+            } else {
+                self.redact_json(change);
+            }
+        }
+    }
+
+    /// Mask credential fields nested in JSON columns
+    fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_redacted(key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact_json(v)),
+            _ => {}
+        }
+    }
+
+    /// A page of matching entries, newest first; continue with `before_id`
+    /// set from the decoded `next_cursor`
+    pub async fn search(&self, filter: &AuditFilter, limit: i64) -> Result<CursorPage<AuditEntry>> {
+        let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+        let rows = self.repository.search(filter, limit + 1).await?;
+        Ok(CursorPage::from_rows(rows, limit, |entry| Cursor::new(entry.created_at, entry.id)))
+    }
+
+    pub async fn get(&self, id: i64) -> Result<AuditEntry> {
+        self.repository
+            .get(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Audit entry not found"))
+    }
+
+    /// Delete entries older than `retention_days`; returns how many
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let cutoff = Utc::now() - Duration::days(i64::from(self.config.retention_days));
+        self.repository.purge_before(cutoff).await
+    }
+}
+
+/// The `audit_retention` recurring job
+pub struct AuditRetentionJob<R: AuditRepository> {
+    audit: Arc<AuditService<R>>,
+}
+
+impl<R: AuditRepository> AuditRetentionJob<R> {
+    pub fn new(audit: Arc<AuditService<R>>) -> Self {
+        Self { audit }
+    }
+}
+
+#[async_trait]
+impl<R: AuditRepository + 'static> RecurringJob for AuditRetentionJob<R> {
+    fn name(&self) -> &str {
+        "audit_retention"
+    }
+
+    fn description(&self) -> &str {
+        "Delete audit log entries past their retention period"
+    }
+
+    fn default_schedule(&self) -> String {
+        "45 3 * * *".to_string()
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(format!("purged {} entries", self.audit.purge_expired().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AuditActorType;
+    use chrono::DateTime;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockRepository {
+        written: Mutex<Vec<NewAuditEntry>>,
+    }
+
+    #[async_trait]
+    impl AuditRepository for MockRepository {
+        async fn insert(&self, entry: &NewAuditEntry) -> Result<()> {
+            self.written.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+
+        async fn snapshot(&self, _table: &str, _id: Uuid) -> Result<Option<Value>> {
+            Ok(None)
+        }
+
+        async fn search(&self, _filter: &AuditFilter, _limit: i64) -> Result<Vec<AuditEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn get(&self, _id: i64) -> Result<Option<AuditEntry>> {
+            Ok(None)
+        }
+
+        async fn purge_before(&self, _before: DateTime<Utc>) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_audit_target() {
+        let id = Uuid::new_v4().to_string();
+        let variant_id = Uuid::new_v4().to_string();
+
+        let target = audit_target("PUT", &format!("/api/v1/products/{}", id)).unwrap();
+        assert_eq!(target.resource_type, "products");
+        assert_eq!(target.resource_id.as_deref(), Some(id.as_str()));
+        assert_eq!(target.table, Some("products"));
+
+        let target = audit_target("PUT", &format!("/api/v1/products/{}/variants/{}", id, variant_id)).unwrap();
+        assert_eq!(target.table, Some("product_variants"));
+        assert_eq!(target.resource_id.as_deref(), Some(variant_id.as_str()));
+
+        let target = audit_target("POST", &format!("/api/v1/admin/payments/{}/refund", id)).unwrap();
+        assert_eq!((target.table, target.created), (Some("payments"), false));
+
+        let target = audit_target("POST", &format!("/api/v1/products/{}/variants", id)).unwrap();
+        assert_eq!((target.table, target.created), (Some("product_variants"), true));
+
+        let target = audit_target("POST", "/api/v1/admin/api-keys/ak_partner/revoke").unwrap();
+        assert_eq!((target.resource_type.as_str(), target.table, target.resource_id), ("api-keys", None, None));
+    }
+
+    #[test]
+    fn test_created_id() {
+        assert_eq!(created_id(&json!({"id": "a1"})).as_deref(), Some("a1"));
+        assert_eq!(created_id(&json!({"product": {"id": "b2"}, "meta": 1})).as_deref(), Some("b2"));
+        assert_eq!(created_id(&json!({"ok": true})), None);
+    }
+
+    #[tokio::test]
+    async fn test_record_redacts_credentials() {
+        let config = AuditConfig { enabled: true, redact_fields: vec!["tax_id".to_string()], ..Default::default() };
+        let service = AuditService::new(MockRepository::default(), config);
+        let entry = NewAuditEntry {
+            actor_type: AuditActorType::User,
+            actor_id: Some(Uuid::new_v4()),
+            actor_label: Some("admin@example.com".to_string()),
+            method: "PUT".to_string(),
+            path: "/api/v1/customers/x".to_string(),
+            route: None,
+            resource_type: Some("customers".to_string()),
+            resource_id: None,
+            status: 200,
+            changes: None,
+            client_ip: None,
+        };
+        let before = json!({"email": "a@x.com", "password_hash": "h1", "tax_id": "1", "metadata": {"api_secret": "s1"}});
+        let after = json!({"email": "b@x.com", "password_hash": "h2", "tax_id": "2", "metadata": {"api_secret": "s2"}});
+        service.record(entry, Some(before), Some(after)).await.unwrap();
+
+        let changes = service.repository.written.lock().unwrap()[0].changes.clone().unwrap();
+        assert_eq!(changes["email"], json!({"before": "a@x.com", "after": "b@x.com"}));
+        assert_eq!(changes["password_hash"], json!({"before": REDACTED, "after": REDACTED}));
+        assert_eq!(changes["tax_id"]["after"], REDACTED);
+        assert_eq!(changes["metadata"]["after"]["api_secret"], REDACTED);
+    }
+
+    #[test]
+    fn test_only_mutations_audited() {
+        let config = AuditConfig { enabled: true, ..Default::default() };
+        let service = AuditService::new(MockRepository::default(), config);
+        assert!(service.is_audited("PUT", "/api/v1/products/1"));
+        assert!(!service.is_audited("GET", "/api/v1/products/1"));
+        assert!(!service.is_audited("POST", "/api/v1/carts/1/items"));
+        assert!(!AuditService::new(MockRepository::default(), AuditConfig::default()).is_audited("PUT", "/api/v1/products/1"));
+    }
+}
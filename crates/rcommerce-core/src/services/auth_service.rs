@@ -10,6 +10,9 @@ use chrono::{Utc, Duration};
 
 use crate::{Result, Error, Config};
 
+/// Prefix marking publishable storefront keys
+pub const PUBLISHABLE_KEY_PREFIX: &str = "pk_";
+
 /// Argon2id hasher instance
 fn argon2() -> Argon2<'static> {
     Argon2::default()
@@ -139,7 +142,18 @@ impl AuthService {
     
     /// Generate API key
     pub fn generate_api_key(&self) -> ApiKey {
-        let prefix = self.generate_prefix();
+        self.api_key_with_prefix(self.generate_prefix())
+    }
+    
+    /// Generate a publishable storefront key
+    ///
+    /// The `pk_` prefix makes these easy to tell apart from secret keys
+    /// in code and logs.
+    pub fn generate_publishable_key(&self) -> ApiKey {
+        self.api_key_with_prefix(format!("{}{}", PUBLISHABLE_KEY_PREFIX, self.generate_prefix()))
+    }
+    
+    fn api_key_with_prefix(&self, prefix: String) -> ApiKey {
         let secret = self.generate_secret();
         let full_key = format!("{}.{}", prefix, secret);
         
@@ -172,9 +186,43 @@ impl AuthService {
     
     /// Generate JWT access token for a customer with role-based permissions
     pub fn generate_access_token(&self, customer_id: Uuid, email: &str, role: &crate::models::CustomerRole) -> Result<String> {
-        let expiry_hours = self.config.security.jwt.expiry_hours as i64;
+        self.generate_access_token_with_permissions(customer_id, email, role.permissions())
+    }
+    
+    /// Generate JWT access token carrying the given permissions (e.g. an
+    /// account's base role plus its assigned staff roles)
+    pub fn generate_access_token_with_permissions(&self, customer_id: Uuid, email: &str, permissions: Vec<String>) -> Result<String> {
+        self.access_token(customer_id, email, permissions, None)
+    }
+    
+    /// Generate JWT access token for a login session, renewed at `/auth/refresh`
+    pub fn generate_session_access_token(&self, customer_id: Uuid, email: &str, permissions: Vec<String>, session_id: Uuid) -> Result<String> {
+        self.access_token(customer_id, email, permissions, Some(session_id))
+    }
+    
+    /// Access token lifetime in seconds
+    pub fn access_token_ttl_secs(&self) -> i64 {
+        self.config.security.jwt.access_token_ttl_secs()
+    }
+    
+    /// Token lifetimes and signing secret
+    pub fn jwt_config(&self) -> &crate::config::JwtConfig {
+        &self.config.security.jwt
+    }
+    
+    /// Two-factor authentication policy
+    pub fn two_factor_config(&self) -> &crate::config::TwoFactorConfig {
+        &self.config.security.two_factor
+    }
+    
+    /// Social login providers
+    pub fn oauth_config(&self) -> &crate::config::OAuthConfig {
+        &self.config.security.oauth
+    }
+    
+    fn access_token(&self, customer_id: Uuid, email: &str, permissions: Vec<String>, sid: Option<Uuid>) -> Result<String> {
         let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(expiry_hours))
+            .checked_add_signed(Duration::seconds(self.access_token_ttl_secs()))
             .expect("valid timestamp")
             .timestamp();
         
@@ -182,11 +230,12 @@ impl AuthService {
             sub: customer_id,
             email: email.to_string(),
             token_type: TokenType::Access,
-            permissions: role.permissions(),
+            permissions,
             exp: expiration,
             iat: Utc::now().timestamp(),
             iss: "rcommerce".to_string(),
             aud: "rcommerce-api".to_string(),
+            sid,
         };
         
         let header = Header::new(jsonwebtoken::Algorithm::HS256);
@@ -196,32 +245,6 @@ impl AuthService {
             .map_err(|e| Error::internal(format!("Failed to generate JWT: {}", e)))
     }
     
-    /// Generate JWT refresh token
-    pub fn generate_refresh_token(&self, customer_id: Uuid) -> Result<String> {
-        // Refresh tokens last 7 days
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(24 * 7))
-            .expect("valid timestamp")
-            .timestamp();
-        
-        let claims = JwtClaims {
-            sub: customer_id,
-            email: String::new(), // Refresh tokens don't need email
-            token_type: TokenType::Refresh,
-            permissions: vec!["refresh".to_string()],
-            exp: expiration,
-            iat: Utc::now().timestamp(),
-            iss: "rcommerce".to_string(),
-            aud: "rcommerce-api".to_string(),
-        };
-        
-        let header = Header::new(jsonwebtoken::Algorithm::HS256);
-        let encoding_key = EncodingKey::from_secret(self.config.security.jwt.secret.as_bytes());
-        
-        encode(&header, &claims, &encoding_key)
-            .map_err(|e| Error::internal(format!("Failed to generate refresh token: {}", e)))
-    }
-    
     /// Verify and decode a JWT token
     pub fn verify_token(&self, token: &str) -> Result<JwtClaims> {
         let decoding_key = DecodingKey::from_secret(self.config.security.jwt.secret.as_bytes());
@@ -267,6 +290,7 @@ impl AuthService {
             iat: Utc::now().timestamp(),
             iss: "rcommerce".to_string(),
             aud: "rcommerce-api".to_string(),
+            sid: None,
         };
         
         let header = Header::new(jsonwebtoken::Algorithm::HS256);
@@ -327,12 +351,16 @@ pub struct JwtClaims {
     pub iat: i64,  // Issued at
     pub iss: String, // Issuer
     pub aud: String, // Audience
+    /// Login session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum TokenType {
     Access,
+    /// Stateless refresh tokens of older releases; login sessions replaced them
     Refresh,
     PasswordReset,
 }
@@ -370,6 +398,10 @@ mod tests {
         // Test invalid key
         let is_valid = auth.verify_api_key("invalid.key", &api_key.hash).unwrap();
         assert!(!is_valid);
+        
+        let publishable = auth.generate_publishable_key();
+        assert!(publishable.prefix.starts_with(PUBLISHABLE_KEY_PREFIX));
+        assert!(auth.verify_api_key(&publishable.full_key.unwrap(), &publishable.hash).unwrap());
     }
     
     #[test]
@@ -386,7 +418,26 @@ mod tests {
         // Verify wrong password fails
         assert!(!auth.verify_password("wrong_password", &hash).unwrap().0);
     }
-    
+
+    #[test]
+    fn test_session_access_token() {
+        let mut config = test_config();
+        config.security.jwt.access_token_ttl_mins = Some(15);
+        let auth = AuthService::new(config);
+
+        let session_id = Uuid::new_v4();
+        let token = auth
+            .generate_session_access_token(Uuid::new_v4(), "test@example.com", vec!["read".to_string()], session_id)
+            .unwrap();
+        let claims = auth.verify_token(&token).unwrap();
+        assert_eq!(claims.sid, Some(session_id));
+        assert!((claims.exp - claims.iat - 15 * 60).abs() <= 1);
+
+        // Tokens without a session leave the claim out
+        let token = auth.generate_access_token_with_permissions(Uuid::new_v4(), "test@example.com", Vec::new()).unwrap();
+        assert_eq!(auth.verify_token(&token).unwrap().sid, None);
+    }
+
     #[test]
     fn test_jwt_generation_and_verification() {
         use crate::models::CustomerRole;
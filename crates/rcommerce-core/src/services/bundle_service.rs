@@ -1,8 +1,15 @@
 //! Bundle Product Service
 //!
 //! Handles bundle product component management, pricing calculations, and cart expansion.
+//!
+//! A bundle is stocked through its required components: it is available as
+//! often as their free stock makes it up, and selling one reserves the
+//! components (see `stock_lines`). Prices of `sum` and `percentage_discount`
+//! bundles are kept in step with their components by the database.
+
+use std::collections::BTreeMap;
 
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use uuid::Uuid;
 use sqlx::Row;
 
@@ -206,7 +213,7 @@ impl BundleService {
                 p.subscription_min_cycles, p.subscription_max_cycles,
                 p.file_url, p.file_size, p.file_hash, p.download_limit,
                 p.license_key_enabled, p.download_expiry_days,
-                p.bundle_pricing_strategy, p.bundle_discount_percentage
+                p.bundle_pricing_strategy, p.bundle_discount_percentage, p.vendor
             FROM bundle_components bc
             JOIN products p ON bc.component_product_id = p.id
             WHERE bc.bundle_product_id = $1
@@ -270,6 +277,7 @@ impl BundleService {
                 download_expiry_days: row.try_get("download_expiry_days")?,
                 bundle_pricing_strategy: row.try_get("bundle_pricing_strategy")?,
                 bundle_discount_percentage: row.try_get("bundle_discount_percentage")?,
+                vendor: row.try_get("vendor")?,
             };
 
             components.push(BundleComponentWithProduct {
@@ -292,39 +300,51 @@ impl BundleService {
 
         let components = self.get_bundle_components(bundle_product_id).await?;
 
-        let strategy = bundle.bundle_pricing_strategy.unwrap_or(BundlePricingStrategy::Fixed);
+        let required: Vec<(Decimal, i32)> = components
+            .iter()
+            .filter(|c| !c.component.is_optional)
+            .map(|c| (c.product.as_ref().map(|p| p.price).unwrap_or(Decimal::ZERO), c.component.quantity))
+            .collect();
 
-        match strategy {
-            BundlePricingStrategy::Fixed => {
-                // Return the fixed price set on the bundle product
-                Ok(bundle.price)
-            }
-            BundlePricingStrategy::Sum => {
-                // Sum of all component prices * quantities
-                let total: Decimal = components.iter()
-                    .filter(|c| !c.component.is_optional) // Only include required components
-                    .map(|c| {
-                        let price = c.product.as_ref().map(|p| p.price).unwrap_or(Decimal::ZERO);
-                        price * Decimal::from(c.component.quantity)
-                    })
-                    .sum();
-                Ok(total)
-            }
-            BundlePricingStrategy::PercentageDiscount => {
-                // Sum of components minus discount percentage
-                let total: Decimal = components.iter()
-                    .filter(|c| !c.component.is_optional)
-                    .map(|c| {
-                        let price = c.product.as_ref().map(|p| p.price).unwrap_or(Decimal::ZERO);
-                        price * Decimal::from(c.component.quantity)
-                    })
-                    .sum();
-
-                let discount_percentage = bundle.bundle_discount_percentage.unwrap_or(Decimal::ZERO);
-                let discount = total * (discount_percentage / Decimal::from(100));
-                Ok(total - discount)
-            }
-        }
+        Ok(bundle_price(
+            bundle.bundle_pricing_strategy.unwrap_or(BundlePricingStrategy::Fixed),
+            bundle.price,
+            bundle.bundle_discount_percentage,
+            &required,
+        ))
+    }
+
+    /// Bundles the free stock of the required components makes up; None
+    /// when no component's stock limits it
+    pub async fn available_quantity(&self, bundle_product_id: Uuid) -> Result<Option<i32>> {
+        // A reservation is taken at a single location, so a component counts
+        // with its best stocked location
+        let components = sqlx::query_as::<_, (i32, Option<i64>)>(
+            r#"
+            SELECT bc.quantity,
+                   CASE WHEN p.inventory_management AND p.inventory_policy = 'deny' THEN
+                       COALESCE((
+                           SELECT MAX(l.available_quantity - COALESCE((
+                               SELECT SUM(r.quantity) FROM stock_reservations r
+                               WHERE r.product_id = l.product_id AND r.variant_id IS NULL
+                                 AND r.location_id = l.location_id
+                                 AND r.status = 'active' AND r.expires_at > NOW()
+                           ), 0))
+                           FROM inventory_levels l
+                           JOIN inventory_locations loc ON loc.id = l.location_id AND loc.is_active
+                           WHERE l.product_id = p.id AND l.variant_id IS NULL
+                       ), 0)::BIGINT
+                   END
+            FROM bundle_components bc
+            JOIN products p ON p.id = bc.component_product_id
+            WHERE bc.bundle_product_id = $1 AND NOT bc.is_optional
+            "#
+        )
+        .bind(bundle_product_id)
+        .fetch_all(self.db.pool())
+        .await?;
+
+        Ok(bundle_availability(&components))
     }
 
     /// Expand a bundle into its components for cart/order
@@ -401,7 +421,8 @@ impl BundleService {
         Ok(true)
     }
 
-    /// Update bundle product price based on strategy
+    /// Update bundle product price based on strategy; the database does
+    /// this on every component change, so this only repairs drift
     pub async fn update_bundle_price(&self, bundle_product_id: Uuid) -> Result<Product> {
         let bundle = sqlx::query_as::<_, Product>(
             "SELECT * FROM products WHERE id = $1"
@@ -500,9 +521,96 @@ impl BundleService {
     }
 }
 
+/// Price of a bundle from its required components' (price, quantity): the
+/// bundle's own price when fixed, else their sum less the discount, to the
+/// cent (as `refresh_bundle_price` in the database rounds it)
+pub fn bundle_price(
+    strategy: BundlePricingStrategy,
+    fixed_price: Decimal,
+    discount_percentage: Option<Decimal>,
+    components: &[(Decimal, i32)],
+) -> Decimal {
+    let sum: Decimal = components.iter().map(|(price, quantity)| *price * Decimal::from(*quantity)).sum();
+    let price = match strategy {
+        BundlePricingStrategy::Fixed => return fixed_price,
+        BundlePricingStrategy::Sum => sum,
+        BundlePricingStrategy::PercentageDiscount => {
+            sum * (Decimal::ONE - discount_percentage.unwrap_or(Decimal::ZERO) / Decimal::from(100))
+        }
+    };
+    price.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Bundles made up by the required components' (quantity per bundle, free
+/// stock), the free stock being None when the component isn't limited
+pub fn bundle_availability(components: &[(i32, Option<i64>)]) -> Option<i32> {
+    components
+        .iter()
+        .filter(|(quantity, _)| *quantity > 0)
+        .filter_map(|(quantity, free)| free.map(|free| free.max(0) / *quantity as i64))
+        .min()
+        .map(|available| available.min(i32::MAX as i64) as i32)
+}
+
+/// The stock order items take: (product, variant, quantity) with bundles
+/// replaced by their required components (bundle, component, quantity per
+/// bundle), merged per product and variant and in lock order
+pub fn stock_lines(
+    items: &[(Uuid, Option<Uuid>, i32)],
+    components: &[(Uuid, Uuid, i32)],
+) -> Vec<(Uuid, Option<Uuid>, i32)> {
+    let mut lines: BTreeMap<(Uuid, Option<Uuid>), i32> = BTreeMap::new();
+    for &(product_id, variant_id, quantity) in items {
+        let mut parts = components.iter().filter(|(bundle_id, _, _)| *bundle_id == product_id).peekable();
+        if parts.peek().is_none() {
+            *lines.entry((product_id, variant_id)).or_default() += quantity;
+            continue;
+        }
+        for &(_, component_id, per_bundle) in parts {
+            *lines.entry((component_id, None)).or_default() += per_bundle * quantity;
+        }
+    }
+    lines.into_iter().map(|((product_id, variant_id), quantity)| (product_id, variant_id, quantity)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Tests would require a database connection
+    #[test]
+    fn test_bundle_price() {
+        let components = [(Decimal::new(1000, 2), 2), (Decimal::new(550, 2), 1)];
+        assert_eq!(bundle_price(BundlePricingStrategy::Fixed, Decimal::new(2000, 2), None, &components), Decimal::new(2000, 2));
+        assert_eq!(bundle_price(BundlePricingStrategy::Sum, Decimal::ZERO, None, &components), Decimal::new(2550, 2));
+        // 25.50 less 15% is 21.675
+        assert_eq!(
+            bundle_price(BundlePricingStrategy::PercentageDiscount, Decimal::ZERO, Some(Decimal::from(15)), &components),
+            Decimal::new(2168, 2)
+        );
+        assert_eq!(bundle_price(BundlePricingStrategy::Sum, Decimal::ONE, None, &[]), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_bundle_availability() {
+        assert_eq!(bundle_availability(&[(2, Some(7)), (1, Some(5)), (3, None)]), Some(3));
+        assert_eq!(bundle_availability(&[(2, Some(-4)), (1, Some(5))]), Some(0));
+        assert_eq!(bundle_availability(&[(1, None)]), None);
+        assert_eq!(bundle_availability(&[]), None);
+    }
+
+    #[test]
+    fn test_stock_lines() {
+        let (bundle, a, b, simple) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let variant = Uuid::new_v4();
+        let components = [(bundle, a, 2), (bundle, b, 1)];
+        let mut lines = stock_lines(&[(bundle, None, 3), (a, None, 1), (simple, Some(variant), 2)], &components);
+
+        let mut expected = vec![(a, None, 7), (b, None, 3), (simple, Some(variant), 2)];
+        expected.sort();
+        assert_eq!(lines, expected);
+
+        // A bundle without components is stocked itself
+        lines = stock_lines(&[(bundle, None, 1)], &[]);
+        assert_eq!(lines, vec![(bundle, None, 1)]);
+    }
 }
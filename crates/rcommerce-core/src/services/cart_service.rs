@@ -1,7 +1,8 @@
 //! Cart Service
 //!
 //! Handles all cart-related business logic including bundle product expansion,
-//! tax calculation, and checkout preparation.
+//! tax calculation, and checkout preparation. Quantities are checked
+//! against purchase limits and allocation lists as items are added.
 
 use std::sync::Arc;
 
@@ -14,10 +15,10 @@ use crate::{
     Error, Result,
     models::{
         Cart, CartItem, CartWithItems, CartIdentifier, AddToCartInput, 
-        UpdateCartItemInput, ApplyCouponInput, Address,
+        UpdateCartItemInput, ApplyCouponInput, Address, Buyer, PurchaseLine,
     },
     repository::{CartRepository, CouponRepository, Database},
-    services::{CouponService, BundleService},
+    services::{CouponService, BundleService, PurchaseLimiter},
     tax::{
         TaxService, TaxContext, TaxAddress, TaxableItem, CustomerTaxInfo,
         TransactionType, VatId,
@@ -31,6 +32,7 @@ pub struct CartService {
     coupon_service: Arc<CouponService>,
     tax_service: Option<Arc<dyn TaxService>>,
     bundle_service: Option<Arc<BundleService>>,
+    purchase_limits: Option<PurchaseLimiter>,
     db: Option<Database>,
 }
 
@@ -47,6 +49,7 @@ impl CartService {
             coupon_service,
             tax_service: None,
             bundle_service: None,
+            purchase_limits: None,
             db: None,
         }
     }
@@ -69,6 +72,15 @@ impl CartService {
         self
     }
 
+    /// Refuse cart quantities over a purchase limit or held back by an allocation list
+    pub fn with_purchase_limits(
+        mut self,
+        purchase_limits: PurchaseLimiter,
+    ) -> Self {
+        self.purchase_limits = Some(purchase_limits);
+        self
+    }
+
     /// Create a new cart service with database access (for bundle expansion)
     pub fn with_database(
         mut self,
@@ -210,9 +222,14 @@ impl CartService {
         }
 
         // Check if item already exists in cart
-        if let Some(mut existing_item) = self.cart_repo.find_item(cart_id, input.product_id, input.variant_id).await? {
-            // Update quantity
-            existing_item.quantity += input.quantity;
+        let existing_item = self.cart_repo.find_item(cart_id, input.product_id, input.variant_id).await?;
+        let quantity = input.quantity + existing_item.as_ref().map(|item| item.quantity).unwrap_or(0);
+        self.check_purchase_limits(&cart, input.product_id, input.variant_id, quantity).await?;
+
+        if let Some(mut existing_item) = existing_item {
+            // Update quantity, and the price for it (quantity tiers)
+            existing_item.quantity = quantity;
+            existing_item.unit_price = product_details.unit_price;
             existing_item.calculate_totals();
             self.cart_repo.update_item(&existing_item).await?;
             
@@ -356,6 +373,12 @@ impl CartService {
             self.recalculate_cart(cart_id).await?;
             Ok(item)
         } else {
+            let cart = self.cart_repo
+                .find_by_id(cart_id)
+                .await?
+                .ok_or_else(|| Error::not_found("Cart not found"))?;
+            self.check_purchase_limits(&cart, item.product_id, item.variant_id, input.quantity).await?;
+
             item.quantity = input.quantity;
             item.custom_attributes = input.custom_attributes;
             item.calculate_totals();
@@ -490,6 +513,36 @@ impl CartService {
         Ok(())
     }
 
+    /// Check the cart against purchase limits with `quantity` of one product/variant
+    /// in place of what the cart holds now
+    async fn check_purchase_limits(
+        &self,
+        cart: &Cart,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        quantity: i32,
+    ) -> Result<()> {
+        let Some(purchase_limits) = &self.purchase_limits else {
+            return Ok(());
+        };
+
+        let mut lines: Vec<PurchaseLine> = self.cart_repo
+            .get_items(cart.id)
+            .await?
+            .iter()
+            .filter(|item| !(item.product_id == product_id && item.variant_id == variant_id))
+            .map(PurchaseLine::from)
+            .collect();
+        lines.push(PurchaseLine { product_id, variant_id, quantity: i64::from(quantity) });
+
+        let buyer = Buyer {
+            customer_id: cart.customer_id,
+            email: cart.email.clone(),
+            household_key: None,
+        };
+        purchase_limits.check(&buyer, &lines, Utc::now()).await
+    }
+
     /// Recalculate cart totals
     async fn recalculate_cart(&self, cart_id: Uuid) -> Result<()> {
         let items = self.cart_repo.get_items(cart_id).await?;
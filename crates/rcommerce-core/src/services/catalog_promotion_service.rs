@@ -0,0 +1,352 @@
+//! Catalog Promotion Service
+//!
+//! Compares catalogs between instances (e.g. staging and production) and
+//! applies approved changes, so merchandising prepared and tested on
+//! staging can be promoted without re-entering it. Records are matched by
+//! natural key: product and category slugs, tax zone and tax category
+//! codes, tax rates by zone, category and start date, and shipping rules
+//! by name.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::models::{
+    CatalogApplyResult, CatalogCategory, CatalogChange, CatalogChangeAction, CatalogDiffOptions, CatalogEntity,
+    CatalogProduct, CatalogRecord, CatalogSnapshot, CatalogTaxCategory, CatalogTaxRate, CatalogTaxZone,
+};
+use crate::repository::CatalogRepository;
+use crate::shipping::ShippingRule;
+use crate::{Error, Result};
+
+/// Catalog promotion service
+pub struct CatalogPromotionService<R: CatalogRepository> {
+    repository: R,
+}
+
+impl<R: CatalogRepository> CatalogPromotionService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    /// This instance's catalog
+    pub async fn snapshot(&self) -> Result<CatalogSnapshot> {
+        self.repository.snapshot().await
+    }
+
+    /// Changes that would make this instance's catalog match `source`
+    pub async fn diff(&self, source: &CatalogSnapshot, options: &CatalogDiffOptions) -> Result<Vec<CatalogChange>> {
+        let target = self.repository.snapshot().await?;
+        Ok(diff_catalogs(source, &target, options))
+    }
+
+    /// Apply approved changes to this instance in one transaction
+    pub async fn apply(&self, changes: Vec<CatalogChange>) -> Result<CatalogApplyResult> {
+        let mut seen = HashSet::new();
+        for change in &changes {
+            if !seen.insert((change.entity, change.key.as_str())) {
+                return Err(Error::validation(format!(
+                    "{} '{}' is changed more than once",
+                    change.entity, change.key
+                )));
+            }
+            validate_change(change)?;
+        }
+
+        let changes = order_changes(changes);
+        let result = self.repository.apply(&changes).await?;
+        tracing::info!(
+            "Applied catalog changes: {} created, {} updated, {} deleted",
+            result.created,
+            result.updated,
+            result.deleted
+        );
+        Ok(result)
+    }
+}
+
+/// Changes that make `target` match `source`, in the order they apply
+pub fn diff_catalogs(
+    source: &CatalogSnapshot,
+    target: &CatalogSnapshot,
+    options: &CatalogDiffOptions,
+) -> Vec<CatalogChange> {
+    let wanted = |entity: CatalogEntity| options.entities.is_empty() || options.entities.contains(&entity);
+    let deletes = options.include_deletes;
+
+    let mut changes = Vec::new();
+    if wanted(CatalogEntity::TaxCategory) {
+        changes.extend(diff_records::<CatalogTaxCategory>(&source.tax_categories, &target.tax_categories, deletes));
+    }
+    if wanted(CatalogEntity::TaxZone) {
+        changes.extend(diff_records::<CatalogTaxZone>(&source.tax_zones, &target.tax_zones, deletes));
+    }
+    if wanted(CatalogEntity::Category) {
+        changes.extend(diff_records::<CatalogCategory>(&source.categories, &target.categories, deletes));
+    }
+    if wanted(CatalogEntity::Product) {
+        // Deleting a product deactivates it, so inactive ones are already gone
+        let inactive: HashSet<&str> = target
+            .products
+            .iter()
+            .filter(|product| !product.is_active)
+            .map(|product| product.slug.as_str())
+            .collect();
+        changes.extend(
+            diff_records::<CatalogProduct>(&source.products, &target.products, deletes)
+                .into_iter()
+                .filter(|change| {
+                    change.action != CatalogChangeAction::Delete || !inactive.contains(change.key.as_str())
+                }),
+        );
+    }
+    if wanted(CatalogEntity::TaxRate) {
+        changes.extend(diff_records::<CatalogTaxRate>(&source.tax_rates, &target.tax_rates, deletes));
+    }
+    if wanted(CatalogEntity::ShippingRule) {
+        changes.extend(diff_records::<ShippingRule>(&source.shipping_rules, &target.shipping_rules, deletes));
+    }
+    order_changes(changes)
+}
+
+fn diff_records<T: CatalogRecord>(source: &[T], target: &[T], include_deletes: bool) -> Vec<CatalogChange> {
+    let target_by_key: BTreeMap<String, &T> = target.iter().map(|record| (record.key(), record)).collect();
+    let source_keys: HashSet<String> = source.iter().map(CatalogRecord::key).collect();
+
+    let mut changes = Vec::new();
+    for record in source {
+        let key = record.key();
+        let change = match target_by_key.get(&key) {
+            None => CatalogChange {
+                entity: T::ENTITY,
+                key,
+                action: CatalogChangeAction::Create,
+                fields: Vec::new(),
+                record: serde_json::to_value(record).ok(),
+            },
+            Some(existing) if *existing == record => continue,
+            Some(existing) => CatalogChange {
+                entity: T::ENTITY,
+                key,
+                action: CatalogChangeAction::Update,
+                fields: changed_fields(record, *existing),
+                record: serde_json::to_value(record).ok(),
+            },
+        };
+        changes.push(change);
+    }
+
+    if include_deletes {
+        changes.extend(
+            target_by_key
+                .into_keys()
+                .filter(|key| !source_keys.contains(key))
+                .map(|key| CatalogChange {
+                    entity: T::ENTITY,
+                    key,
+                    action: CatalogChangeAction::Delete,
+                    fields: Vec::new(),
+                    record: None,
+                }),
+        );
+    }
+    changes
+}
+
+/// Top-level fields whose serialized values differ
+fn changed_fields<T: CatalogRecord>(source: &T, target: &T) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(source)), Ok(serde_json::Value::Object(target))) =
+        (serde_json::to_value(source), serde_json::to_value(target))
+    else {
+        return Vec::new();
+    };
+    source
+        .into_iter()
+        .filter(|(field, value)| target.get(field) != Some(value))
+        .map(|(field, _)| field)
+        .collect()
+}
+
+/// Creates and updates with referenced records first, then deletes with
+/// referencing records first
+fn order_changes(mut changes: Vec<CatalogChange>) -> Vec<CatalogChange> {
+    changes.sort_by_key(|change| {
+        let rank = change.entity as i32;
+        match change.action {
+            CatalogChangeAction::Delete => (1, -rank),
+            _ => (0, rank),
+        }
+    });
+    changes
+}
+
+/// A change's record must decode and match its key
+fn validate_change(change: &CatalogChange) -> Result<()> {
+    if change.action == CatalogChangeAction::Delete {
+        return Ok(());
+    }
+    let key = match change.entity {
+        CatalogEntity::TaxCategory => change.decode::<CatalogTaxCategory>()?.key(),
+        CatalogEntity::TaxZone => change.decode::<CatalogTaxZone>()?.key(),
+        CatalogEntity::Category => change.decode::<CatalogCategory>()?.key(),
+        CatalogEntity::Product => change.decode::<CatalogProduct>()?.key(),
+        CatalogEntity::TaxRate => change.decode::<CatalogTaxRate>()?.key(),
+        CatalogEntity::ShippingRule => change.decode::<ShippingRule>()?.key(),
+    };
+    if key != change.key {
+        return Err(Error::validation(format!(
+            "{} '{}' has a record for '{}'",
+            change.entity, change.key, key
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shipping::{RuleAction, RuleCondition};
+    use rust_decimal_macros::dec;
+
+    fn category(slug: &str, name: &str) -> CatalogCategory {
+        CatalogCategory {
+            slug: slug.to_string(),
+            name: name.to_string(),
+            description: None,
+            parent_slug: None,
+            image_url: None,
+            seo_title: None,
+            seo_description: None,
+            sort_order: 0,
+            is_active: true,
+        }
+    }
+
+    fn product(slug: &str, price: rust_decimal::Decimal, is_active: bool) -> CatalogProduct {
+        CatalogProduct {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            description: None,
+            sku: None,
+            product_type: "simple".to_string(),
+            price,
+            compare_at_price: None,
+            cost_price: None,
+            currency: "USD".to_string(),
+            weight: None,
+            weight_unit: None,
+            requires_shipping: true,
+            is_active,
+            is_featured: false,
+            seo_title: None,
+            seo_description: None,
+            tax_category_code: None,
+            category_slugs: vec!["shoes".to_string()],
+            primary_category_slug: Some("shoes".to_string()),
+        }
+    }
+
+    fn summary(changes: &[CatalogChange]) -> Vec<(CatalogEntity, &str, CatalogChangeAction)> {
+        changes.iter().map(|c| (c.entity, c.key.as_str(), c.action)).collect()
+    }
+
+    #[test]
+    fn test_diff_creates_updates_and_deletes() {
+        let source = CatalogSnapshot {
+            categories: vec![category("shoes", "Shoes"), category("hats", "Hats")],
+            products: vec![product("runner", dec!(120.00), true)],
+            shipping_rules: vec![ShippingRule::new(
+                "Free over 100",
+                RuleCondition::OrderTotal { min: Some(dec!(100)), max: None },
+                RuleAction::FreeShipping,
+            )],
+            ..Default::default()
+        };
+        let target = CatalogSnapshot {
+            categories: vec![category("shoes", "Footwear"), category("bags", "Bags")],
+            products: vec![product("runner", dec!(100.00), true), product("sandal", dec!(40.00), true)],
+            ..Default::default()
+        };
+
+        let changes = diff_catalogs(&source, &target, &CatalogDiffOptions::default());
+        assert_eq!(
+            summary(&changes),
+            vec![
+                (CatalogEntity::Category, "shoes", CatalogChangeAction::Update),
+                (CatalogEntity::Category, "hats", CatalogChangeAction::Create),
+                (CatalogEntity::Product, "runner", CatalogChangeAction::Update),
+                (CatalogEntity::ShippingRule, "Free over 100", CatalogChangeAction::Create),
+            ]
+        );
+        assert_eq!(changes[0].fields, vec!["name"]);
+        assert_eq!(changes[2].fields, vec!["price"]);
+        assert_eq!(changes[2].decode::<CatalogProduct>().unwrap().price, dec!(120.00));
+
+        // Deletes are opt in, dependents first
+        let options = CatalogDiffOptions { include_deletes: true, ..Default::default() };
+        let changes = diff_catalogs(&source, &target, &options);
+        assert_eq!(
+            summary(&changes)[4..],
+            [
+                (CatalogEntity::Product, "sandal", CatalogChangeAction::Delete),
+                (CatalogEntity::Category, "bags", CatalogChangeAction::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_filters_entities_and_skips_inactive_product_deletes() {
+        let source = CatalogSnapshot {
+            categories: vec![category("hats", "Hats")],
+            ..Default::default()
+        };
+        let target = CatalogSnapshot {
+            products: vec![product("retired", dec!(10.00), false), product("sandal", dec!(40.00), true)],
+            ..Default::default()
+        };
+        let options = CatalogDiffOptions {
+            entities: vec![CatalogEntity::Product],
+            include_deletes: true,
+        };
+
+        let changes = diff_catalogs(&source, &target, &options);
+        assert_eq!(summary(&changes), vec![(CatalogEntity::Product, "sandal", CatalogChangeAction::Delete)]);
+        assert!(diff_catalogs(&target, &target, &options).is_empty());
+    }
+
+    #[test]
+    fn test_validate_change() {
+        let rate = CatalogTaxRate {
+            zone_code: "DE".to_string(),
+            category_code: None,
+            valid_from: chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            name: "VAT".to_string(),
+            rate: dec!(0.19),
+            rate_type: "percentage".to_string(),
+            is_vat: true,
+            vat_type: Some("standard".to_string()),
+            b2b_exempt: false,
+            reverse_charge: true,
+            valid_until: None,
+            priority: 0,
+        };
+        assert_eq!(rate.key(), "DE/*/2024-01-01");
+
+        let mut change = CatalogChange {
+            entity: CatalogEntity::TaxRate,
+            key: rate.key(),
+            action: CatalogChangeAction::Create,
+            fields: Vec::new(),
+            record: serde_json::to_value(&rate).ok(),
+        };
+        assert!(validate_change(&change).is_ok());
+
+        change.key = "FR/*/2024-01-01".to_string();
+        assert!(validate_change(&change).is_err());
+
+        change.record = Some(serde_json::json!({ "zone_code": "FR" }));
+        assert!(validate_change(&change).is_err());
+
+        change.action = CatalogChangeAction::Delete;
+        change.record = None;
+        assert!(validate_change(&change).is_ok());
+    }
+}
@@ -0,0 +1,233 @@
+//! Category Service
+//!
+//! Manages the category tree and which products are in each category. A
+//! category cannot be moved under itself or one of its descendants.
+
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{slugify, CreateCategoryRequest, ProductCategory, UpdateCategoryRequest};
+use crate::repository::{CategoryRepository, CategoryTreeNode};
+use crate::{Error, Result};
+
+/// Category service
+pub struct CategoryService<R: CategoryRepository> {
+    repository: R,
+}
+
+impl<R: CategoryRepository> CategoryService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create a category, by default with a slug from its name
+    pub async fn create_category(&self, request: CreateCategoryRequest) -> Result<ProductCategory> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let name = request.name.trim().to_string();
+        let slug = category_slug(request.slug.as_deref().unwrap_or(&name))?;
+        self.check_slug_free(&slug, None).await?;
+        if let Some(parent_id) = request.parent_id {
+            self.get_category(parent_id).await?;
+        }
+
+        let now = Utc::now();
+        let category = ProductCategory {
+            id: Uuid::new_v4(),
+            name,
+            slug,
+            description: request.description,
+            parent_id: request.parent_id,
+            image_url: request.image_url,
+            sort_order: request.sort_order,
+            is_active: request.is_active,
+            created_at: now,
+            updated_at: now,
+        };
+        self.repository.create(&category).await
+    }
+
+    /// Update a category; moving it under one of its own descendants is
+    /// refused
+    pub async fn update_category(&self, id: Uuid, request: UpdateCategoryRequest) -> Result<ProductCategory> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let mut category = self.get_category(id).await?;
+
+        if let Some(name) = request.name {
+            category.name = name.trim().to_string();
+        }
+        if let Some(slug) = request.slug {
+            let slug = category_slug(&slug)?;
+            if slug != category.slug {
+                self.check_slug_free(&slug, Some(id)).await?;
+                category.slug = slug;
+            }
+        }
+        if let Some(parent_id) = request.parent_id {
+            if let Some(parent_id) = parent_id {
+                self.get_category(parent_id).await?;
+                let subtree = self.repository.get_tree(Some(id)).await?;
+                if parent_id == id || tree_contains(&subtree, parent_id) {
+                    return Err(Error::validation("A category cannot be moved under itself or its subcategories"));
+                }
+            }
+            category.parent_id = parent_id;
+        }
+        if request.description.is_some() {
+            category.description = request.description;
+        }
+        if request.image_url.is_some() {
+            category.image_url = request.image_url;
+        }
+        if let Some(sort_order) = request.sort_order {
+            category.sort_order = sort_order;
+        }
+        if let Some(is_active) = request.is_active {
+            category.is_active = is_active;
+        }
+        self.repository.update(&category).await
+    }
+
+    /// Get a category
+    pub async fn get_category(&self, id: Uuid) -> Result<ProductCategory> {
+        self.repository
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Category not found"))
+    }
+
+    /// Get a category by ID or slug
+    pub async fn resolve_category(&self, id_or_slug: &str) -> Result<ProductCategory> {
+        let category = match Uuid::parse_str(id_or_slug) {
+            Ok(id) => self.repository.get_by_id(id).await?,
+            Err(_) => self.repository.get_by_slug(id_or_slug).await?,
+        };
+        category.ok_or_else(|| Error::not_found("Category not found"))
+    }
+
+    /// Delete a category; its subcategories become roots and its products
+    /// stay in the catalog
+    pub async fn delete_category(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete(id).await? {
+            return Err(Error::not_found("Category not found"));
+        }
+        Ok(())
+    }
+
+    /// The category tree, without inactive categories (and everything under
+    /// them) when `active_only`
+    pub async fn category_tree(&self, active_only: bool) -> Result<Vec<CategoryTreeNode>> {
+        let tree = self.repository.get_tree(None).await?;
+        Ok(if active_only { prune_inactive(tree) } else { tree })
+    }
+
+    /// Put products in a category
+    pub async fn assign_products(&self, category_id: Uuid, product_ids: &[Uuid]) -> Result<()> {
+        self.get_category(category_id).await?;
+        for product_id in product_ids {
+            self.repository.assign_product(*product_id, category_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Take a product out of a category
+    pub async fn remove_product(&self, category_id: Uuid, product_id: Uuid) -> Result<()> {
+        if !self.repository.remove_product(product_id, category_id).await? {
+            return Err(Error::not_found("Product is not in this category"));
+        }
+        Ok(())
+    }
+
+    async fn check_slug_free(&self, slug: &str, id: Option<Uuid>) -> Result<()> {
+        match self.repository.get_by_slug(slug).await? {
+            Some(existing) if Some(existing.id) != id => {
+                Err(Error::validation(format!("A category with the slug {} already exists", slug)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn category_slug(name_or_slug: &str) -> Result<String> {
+    let slug = slugify(name_or_slug);
+    if slug.is_empty() {
+        return Err(Error::validation("Category slugs need at least one letter or digit"));
+    }
+    Ok(slug)
+}
+
+/// Whether a category is somewhere in a (sub)tree
+fn tree_contains(nodes: &[CategoryTreeNode], id: Uuid) -> bool {
+    nodes
+        .iter()
+        .any(|node| node.category.id == id || tree_contains(&node.children, id))
+}
+
+fn prune_inactive(nodes: Vec<CategoryTreeNode>) -> Vec<CategoryTreeNode> {
+    nodes
+        .into_iter()
+        .filter(|node| node.category.is_active)
+        .map(|node| CategoryTreeNode {
+            children: prune_inactive(node.children),
+            ..node
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, is_active: bool, children: Vec<CategoryTreeNode>) -> CategoryTreeNode {
+        let now = Utc::now();
+        CategoryTreeNode {
+            category: ProductCategory {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                slug: slugify(name),
+                description: None,
+                parent_id: None,
+                image_url: None,
+                sort_order: 0,
+                is_active,
+                created_at: now,
+                updated_at: now,
+            },
+            children,
+        }
+    }
+
+    #[test]
+    fn test_tree_contains() {
+        let shirts = node("Shirts", true, vec![]);
+        let shirts_id = shirts.category.id;
+        let tree = vec![node("Clothing", true, vec![node("Tops", true, vec![shirts])])];
+
+        assert!(tree_contains(&tree, shirts_id));
+        assert!(tree_contains(&tree, tree[0].category.id));
+        assert!(!tree_contains(&tree, Uuid::new_v4()));
+    }
+
+    #[test]
+    fn test_prune_inactive() {
+        let tree = vec![
+            node("Clothing", true, vec![node("Tops", false, vec![node("Shirts", true, vec![])]), node("Shoes", true, vec![])]),
+            node("Archive", false, vec![]),
+        ];
+        let pruned = prune_inactive(tree);
+
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].children.len(), 1);
+        assert_eq!(pruned[0].children[0].category.name, "Shoes");
+    }
+
+    #[test]
+    fn test_category_slug() {
+        assert_eq!(category_slug("Summer Sale!").unwrap(), "summer-sale");
+        assert!(category_slug("--").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Checkout Field Service
+//!
+//! Validates answers to the merchant's custom checkout fields against the
+//! schema configured in `[checkout_fields]`, and keeps them on the order.
+//! Answers are normalized before they are stored: text is trimmed, empty
+//! answers are dropped, numbers sent as strings become numbers.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::config::CheckoutFieldsConfig;
+use crate::models::{CheckoutFieldDefinition, CheckoutFieldType, CheckoutFieldValues, OrderCheckoutFields};
+use crate::repository::CheckoutFieldRepository;
+use crate::{Error, Result};
+
+/// The configured custom checkout fields, with their patterns compiled
+#[derive(Debug, Clone, Default)]
+pub struct CheckoutFieldSchema {
+    fields: Vec<CheckoutFieldDefinition>,
+    patterns: HashMap<String, Regex>,
+}
+
+impl CheckoutFieldSchema {
+    /// Build the schema, rejecting invalid field definitions
+    pub fn from_config(config: &CheckoutFieldsConfig) -> Result<Self> {
+        let key_format = Regex::new(r"^[a-z][a-z0-9_]*$").expect("valid regex");
+        let mut keys = HashSet::new();
+        let mut patterns = HashMap::new();
+
+        for field in &config.fields {
+            let invalid = |message: &str| Error::Config(format!("checkout_fields '{}': {}", field.key, message));
+
+            if !key_format.is_match(&field.key) {
+                return Err(invalid("key must be lowercase letters, digits and underscores"));
+            }
+            if !keys.insert(field.key.as_str()) {
+                return Err(invalid("key is defined more than once"));
+            }
+            if field.label.trim().is_empty() {
+                return Err(invalid("label is required"));
+            }
+            match (field.field_type, field.options.is_empty()) {
+                (CheckoutFieldType::Select, true) => return Err(invalid("select fields need options")),
+                (CheckoutFieldType::Select, false) => {}
+                (_, false) => return Err(invalid("options only apply to select fields")),
+                (_, true) => {}
+            }
+            if (field.min_length.is_some() || field.max_length.is_some() || field.pattern.is_some())
+                && !field.field_type.is_text()
+            {
+                return Err(invalid("min_length, max_length and pattern only apply to text, email and phone fields"));
+            }
+            if (field.min.is_some() || field.max.is_some()) && field.field_type != CheckoutFieldType::Number {
+                return Err(invalid("min and max only apply to number fields"));
+            }
+            if field.min_length.zip(field.max_length).is_some_and(|(min, max)| min > max)
+                || field.min.zip(field.max).is_some_and(|(min, max)| min > max)
+            {
+                return Err(invalid("minimum is greater than maximum"));
+            }
+            if let Some(ref pattern) = field.pattern {
+                let regex = Regex::new(pattern).map_err(|e| invalid(&format!("invalid pattern: {}", e)))?;
+                patterns.insert(field.key.clone(), regex);
+            }
+        }
+
+        Ok(Self {
+            fields: config.fields.clone(),
+            patterns,
+        })
+    }
+
+    /// Field definitions, in display order
+    pub fn fields(&self) -> &[CheckoutFieldDefinition] {
+        &self.fields
+    }
+
+    /// Check answers against the schema, returning them normalized
+    pub fn validate(&self, values: CheckoutFieldValues) -> Result<CheckoutFieldValues> {
+        if let Some(key) = values.keys().find(|key| !self.fields.iter().any(|field| field.key == **key)) {
+            return Err(Error::validation(format!("Unknown checkout field '{}'", key)));
+        }
+
+        let mut normalized = CheckoutFieldValues::new();
+        for field in &self.fields {
+            match values.get(&field.key).and_then(|value| self.normalize(field, value).transpose()) {
+                Some(value) => {
+                    normalized.insert(field.key.clone(), value?);
+                }
+                None if field.required => {
+                    return Err(Error::validation(format!("{} is required", field.label)));
+                }
+                None => {}
+            }
+        }
+        Ok(normalized)
+    }
+
+    /// Normalize one answer; None if it was left empty
+    fn normalize(&self, field: &CheckoutFieldDefinition, value: &Value) -> Result<Option<Value>> {
+        let invalid = |message: &str| Error::validation(format!("{} {}", field.label, message));
+
+        match (field.field_type, value) {
+            (_, Value::Null) => Ok(None),
+            (CheckoutFieldType::Checkbox, Value::Bool(checked)) => {
+                Ok((*checked || !field.required).then_some(Value::Bool(*checked)))
+            }
+            (CheckoutFieldType::Checkbox, _) => Err(invalid("must be true or false")),
+            (CheckoutFieldType::Number, Value::Number(number)) => self.check_number(field, number.as_f64()).map(|_| Some(value.clone())),
+            (CheckoutFieldType::Number, Value::String(text)) if text.trim().is_empty() => Ok(None),
+            (CheckoutFieldType::Number, Value::String(text)) => {
+                let number = text.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+                self.check_number(field, number)?;
+                Ok(serde_json::Number::from_f64(number.unwrap_or_default()).map(Value::Number))
+            }
+            (_, Value::String(text)) => {
+                let text = text.trim();
+                if text.is_empty() {
+                    return Ok(None);
+                }
+                self.check_text(field, text)?;
+                Ok(Some(Value::String(text.to_string())))
+            }
+            (CheckoutFieldType::Number, _) => Err(invalid("must be a number")),
+            _ => Err(invalid("must be text")),
+        }
+    }
+
+    fn check_number(&self, field: &CheckoutFieldDefinition, number: Option<f64>) -> Result<()> {
+        let number = number.ok_or_else(|| Error::validation(format!("{} must be a number", field.label)))?;
+        if field.min.is_some_and(|min| number < min) || field.max.is_some_and(|max| number > max) {
+            return Err(Error::validation(format!(
+                "{} must be between {} and {}",
+                field.label,
+                field.min.map_or("-".to_string(), |min| min.to_string()),
+                field.max.map_or("-".to_string(), |max| max.to_string()),
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_text(&self, field: &CheckoutFieldDefinition, text: &str) -> Result<()> {
+        let invalid = |message: String| Error::validation(format!("{} {}", field.label, message));
+
+        match field.field_type {
+            CheckoutFieldType::Email => {
+                let (local, domain) = text.split_once('@').unwrap_or_default();
+                if local.is_empty() || !domain.contains('.') || text.contains(char::is_whitespace) {
+                    return Err(invalid("must be an email address".to_string()));
+                }
+            }
+            CheckoutFieldType::Phone => {
+                let digits = text.chars().filter(char::is_ascii_digit).count();
+                let allowed = text.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c));
+                if !allowed || !(6..=15).contains(&digits) {
+                    return Err(invalid("must be a phone number".to_string()));
+                }
+            }
+            CheckoutFieldType::Select => {
+                if !field.options.iter().any(|option| option == text) {
+                    return Err(invalid(format!("must be one of: {}", field.options.join(", "))));
+                }
+            }
+            CheckoutFieldType::Date => {
+                if NaiveDate::parse_from_str(text, "%Y-%m-%d").is_err() {
+                    return Err(invalid("must be a date (YYYY-MM-DD)".to_string()));
+                }
+            }
+            CheckoutFieldType::Text | CheckoutFieldType::Number | CheckoutFieldType::Checkbox => {}
+        }
+
+        let length = text.chars().count();
+        if field.min_length.is_some_and(|min| length < min) {
+            return Err(invalid(format!("must be at least {} characters", field.min_length.unwrap_or_default())));
+        }
+        if field.max_length.is_some_and(|max| length > max) {
+            return Err(invalid(format!("must be at most {} characters", field.max_length.unwrap_or_default())));
+        }
+        if self.patterns.get(&field.key).is_some_and(|pattern| !pattern.is_match(text)) {
+            return Err(invalid("is not in the expected format".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Checkout field service
+pub struct CheckoutFieldService<R: CheckoutFieldRepository> {
+    repository: R,
+    schema: CheckoutFieldSchema,
+}
+
+impl<R: CheckoutFieldRepository> CheckoutFieldService<R> {
+    pub fn new(repository: R, schema: CheckoutFieldSchema) -> Self {
+        Self { repository, schema }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn schema(&self) -> &CheckoutFieldSchema {
+        &self.schema
+    }
+
+    /// An order's custom checkout field answers
+    pub async fn for_order(&self, order_id: Uuid) -> Result<OrderCheckoutFields> {
+        let custom_fields = self
+            .repository
+            .for_order(order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+        Ok(OrderCheckoutFields { order_id, custom_fields })
+    }
+
+    /// Replace an order's answers (staff corrections), checked against the schema
+    pub async fn update_for_order(&self, order_id: Uuid, values: CheckoutFieldValues) -> Result<OrderCheckoutFields> {
+        let custom_fields = self.schema.validate(values)?;
+        if !self.repository.save_for_order(order_id, &custom_fields).await? {
+            return Err(Error::not_found("Order not found"));
+        }
+        Ok(OrderCheckoutFields { order_id, custom_fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(key: &str, field_type: CheckoutFieldType) -> CheckoutFieldDefinition {
+        CheckoutFieldDefinition {
+            key: key.to_string(),
+            label: key.to_string(),
+            field_type,
+            required: false,
+            help_text: None,
+            options: vec![],
+            min_length: None,
+            max_length: None,
+            pattern: None,
+            min: None,
+            max: None,
+        }
+    }
+
+    fn schema(fields: Vec<CheckoutFieldDefinition>) -> Result<CheckoutFieldSchema> {
+        CheckoutFieldSchema::from_config(&CheckoutFieldsConfig { fields })
+    }
+
+    fn values(value: Value) -> CheckoutFieldValues {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_schema_from_config() {
+        let mut select = field("source", CheckoutFieldType::Select);
+        assert!(schema(vec![select.clone()]).is_err());
+        select.options = vec!["Search".to_string(), "Friend".to_string()];
+        assert!(schema(vec![select.clone()]).is_ok());
+        assert!(schema(vec![select.clone(), select]).is_err());
+
+        let mut text = field("vat_number", CheckoutFieldType::Text);
+        text.pattern = Some("[".to_string());
+        assert!(schema(vec![text]).is_err());
+        assert!(schema(vec![field("VAT number", CheckoutFieldType::Text)]).is_err());
+
+        let mut number = field("seats", CheckoutFieldType::Number);
+        number.max_length = Some(3);
+        assert!(schema(vec![number]).is_err());
+    }
+
+    #[test]
+    fn test_validate_values() {
+        let mut vat = field("vat_number", CheckoutFieldType::Text);
+        vat.required = true;
+        vat.pattern = Some(r"^[A-Z]{2}[0-9A-Z]{2,12}$".to_string());
+        let mut source = field("source", CheckoutFieldType::Select);
+        source.options = vec!["Search".to_string(), "Friend".to_string()];
+        let mut seats = field("seats", CheckoutFieldType::Number);
+        seats.min = Some(1.0);
+        let schema = schema(vec![
+            vat,
+            source,
+            seats,
+            field("delivery_phone", CheckoutFieldType::Phone),
+            field("newsletter", CheckoutFieldType::Checkbox),
+        ])
+        .unwrap();
+
+        let normalized = schema
+            .validate(values(json!({
+                "vat_number": " DE123456789 ",
+                "source": "Friend",
+                "seats": "3",
+                "delivery_phone": "",
+                "newsletter": false,
+            })))
+            .unwrap();
+        assert_eq!(normalized.get("vat_number"), Some(&json!("DE123456789")));
+        assert_eq!(normalized.get("seats"), Some(&json!(3.0)));
+        assert_eq!(normalized.get("newsletter"), Some(&json!(false)));
+        assert!(!normalized.contains_key("delivery_phone"));
+
+        assert!(schema.validate(values(json!({}))).is_err());
+        assert!(schema.validate(values(json!({"vat_number": "not a vat"}))).is_err());
+        assert!(schema.validate(values(json!({"vat_number": "DE123", "source": "TV"}))).is_err());
+        assert!(schema.validate(values(json!({"vat_number": "DE123", "seats": 0}))).is_err());
+        assert!(schema.validate(values(json!({"vat_number": "DE123", "delivery_phone": "call me"}))).is_err());
+        assert!(schema.validate(values(json!({"vat_number": "DE123", "coupon": "X"}))).is_err());
+    }
+
+    #[test]
+    fn test_required_checkbox() {
+        let mut terms = field("accept_terms", CheckoutFieldType::Checkbox);
+        terms.required = true;
+        let schema = schema(vec![terms]).unwrap();
+
+        assert!(schema.validate(values(json!({"accept_terms": true}))).is_ok());
+        assert!(schema.validate(values(json!({"accept_terms": false}))).is_err());
+        assert!(schema.validate(values(json!({"accept_terms": "yes"}))).is_err());
+    }
+}
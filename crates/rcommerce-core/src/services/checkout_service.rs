@@ -3,6 +3,25 @@
 //! Orchestrates the complete checkout flow including cart validation,
 //! shipping calculation, tax calculation, order creation, and payment processing.
 //! This service integrates Cart, Tax, Shipping, Order, and Payment services.
+//! Add-ons chosen on the cart (gift wrap, assembly, ...) are priced and
+//! taxed with the items and recorded on the order. Delivery instructions
+//! and a booked delivery window are checked against the dispatch calendar
+//! and the carrier service before the order is created, as are answers to
+//! the merchant's custom checkout fields. Gift cards entered at checkout
+//! are spent first and the payment gateway charges the rest; if the gateway
+//! payment fails, the gift card redemptions are given back. Items under a
+//! live flash sale need the customer's purchase token, which claims the
+//! units from the sale's Redis stock before the order is created and gives
+//! them back if the checkout fails. Purchase limits and allocation lists
+//! are checked with the buyer's email and shipping address, and the
+//! order's household is recorded for later household limits.
+//! Cross-border orders are quoted duties and import taxes; customers
+//! choose to prepay them (DDP, added to the total) or pay on delivery
+//! (DAP), and the choice is recorded on the order.
+//! Pickup locations with everything in stock are offered as free `pickup`
+//! rates; a pickup order records the location it is collected from.
+//! With fraud screening, orders are scored before they are paid: risky ones
+//! are charged with 3-D Secure, held for review or cancelled.
 
 use std::sync::Arc;
 
@@ -13,19 +32,31 @@ use uuid::Uuid;
 
 use crate::{
     Error, Result,
-    models::{Cart, CartItem, Currency, Address},
+    cache::{FlashSaleClaim, FlashSaleStore},
+    models::{
+        addon_total, household_key, Buyer, Cart, CartAddon, CartItem, CheckoutFieldValues, Currency, Address,
+        GiftCard, PurchaseLine, RiskAction, RiskContext,
+    },
     tax::{
         TaxService, TaxContext, TaxAddress, TaxableItem, CustomerTaxInfo,
         TransactionType, TaxCalculation, VatId,
+        Incoterm, LandedCostLine, LandedCostProvider, LandedCostQuote, LandedCostRequest,
     },
     shipping::{
         ShippingProviderFactory, ShippingRate, Package, RateOptions,
+        DeliveryDetails, DeliveryScheduler, supports_scheduled_delivery,
+        is_pickup_rate, PickupLine, PickupLocation,
+        pickup::{pickup_location_for_rate, pickup_locations_for},
     },
     order::{
         OrderService, CreateOrderRequest, CreateOrderItem, Order,
     },
-    payment::{PaymentGateway, CreatePaymentRequest, PaymentMethod},
-    services::CartService,
+    payment::{PaymentGateway, CreatePaymentRequest, PaymentMethod, Payment, GiftCardTender, TenderPlan},
+    repository::{
+        AddonRepository, CheckoutFieldRepository, CustomsRepository, DeliveryRepository, GiftCardRepository,
+        PickupRepository,
+    },
+    services::{CartService, CheckoutFieldSchema, FraudScreen, PurchaseLimiter},
 };
 
 /// Checkout service that orchestrates the complete checkout flow
@@ -34,8 +65,16 @@ pub struct CheckoutService {
     tax_service: Arc<dyn TaxService>,
     order_service: Arc<OrderService>,
     payment_gateway: Arc<dyn PaymentGateway>,
-    #[allow(dead_code)]
     shipping_factory: Arc<ShippingProviderFactory>,
+    addons: Option<Arc<dyn AddonRepository>>,
+    delivery: Option<(DeliveryScheduler, Arc<dyn DeliveryRepository>)>,
+    checkout_fields: Option<(CheckoutFieldSchema, Arc<dyn CheckoutFieldRepository>)>,
+    gift_cards: Option<Arc<dyn GiftCardRepository>>,
+    flash_sales: Option<FlashSaleStore>,
+    purchase_limits: Option<PurchaseLimiter>,
+    landed_cost: Option<(Arc<dyn LandedCostProvider>, Arc<dyn CustomsRepository>)>,
+    pickup: Option<Arc<dyn PickupRepository>>,
+    fraud: Option<FraudScreen>,
     config: CheckoutConfig,
 }
 
@@ -75,7 +114,10 @@ impl Default for CheckoutConfig {
 pub struct CheckoutSummary {
     pub cart_id: Uuid,
     pub items: Vec<CartItem>,
+    pub addons: Vec<CartAddon>,
     pub subtotal: Decimal,
+    /// Price of the chosen add-ons (not included in `subtotal`)
+    pub addon_total: Decimal,
     pub discount_total: Decimal,
     pub shipping_total: Decimal,
     pub shipping_tax: Decimal,
@@ -87,6 +129,12 @@ pub struct CheckoutSummary {
     pub selected_shipping_rate: Option<ShippingRate>,
     pub tax_breakdown: Vec<TaxBreakdownItem>,
     pub vat_id_valid: Option<bool>,
+    /// Duties and import taxes of a cross-border order
+    pub landed_cost: Option<LandedCostQuote>,
+    /// Chosen incoterm; None for domestic orders
+    pub incoterm: Option<Incoterm>,
+    /// Duties, import taxes and fees prepaid with DDP (included in `total`)
+    pub landed_cost_total: Decimal,
 }
 
 /// Tax breakdown item for display
@@ -108,6 +156,8 @@ pub struct InitiateCheckoutRequest {
     pub vat_id: Option<String>,
     pub customer_id: Option<Uuid>,
     pub currency: Option<Currency>,
+    /// DDP or DAP for cross-border orders (default `landed_cost.default_incoterm`)
+    pub incoterm: Option<Incoterm>,
 }
 
 /// Shipping selection request
@@ -116,6 +166,8 @@ pub struct SelectShippingRequest {
     pub cart_id: Uuid,
     pub shipping_rate: ShippingRate,
     pub package: Package,
+    /// DDP or DAP for cross-border orders (default `landed_cost.default_incoterm`)
+    pub incoterm: Option<Incoterm>,
 }
 
 /// Complete checkout request
@@ -130,15 +182,36 @@ pub struct CompleteCheckoutRequest {
     pub vat_id: Option<String>,
     pub notes: Option<String>,
     pub selected_shipping_rate: ShippingRate,
+    /// Delivery instructions and, if the service supports it, a delivery window
+    pub delivery: Option<DeliveryDetails>,
+    /// Answers to the custom checkout fields, by field key
+    pub custom_fields: CheckoutFieldValues,
+    /// Gift card codes to pay with before `payment_method`
+    pub gift_cards: Vec<String>,
+    /// Purchase tokens for items under a live flash sale
+    pub flash_sale_tokens: Vec<String>,
+    /// DDP or DAP for cross-border orders (default `landed_cost.default_incoterm`)
+    pub incoterm: Option<Incoterm>,
+    /// Buyer's IP address, for fraud screening
+    pub client_ip: Option<String>,
 }
 
 /// Checkout result
 #[derive(Debug, Clone)]
 pub struct CheckoutResult {
     pub order: Order,
-    pub payment_id: String,
+    /// Gateway payment; None if gift cards paid for the whole order
+    pub payment_id: Option<String>,
+    /// Charged through the payment gateway
     pub total_charged: Decimal,
     pub currency: Currency,
+    pub gift_cards: Vec<GiftCardTender>,
+    /// Incoterm recorded on the order; None for domestic orders
+    pub incoterm: Option<Incoterm>,
+    /// Duties, import taxes and fees prepaid with DDP (included in the order total)
+    pub landed_cost_total: Decimal,
+    /// The order is on hold until staff review it for fraud
+    pub held_for_review: bool,
 }
 
 /// Tax calculation result with shipping
@@ -166,10 +239,74 @@ impl CheckoutService {
             order_service,
             payment_gateway,
             shipping_factory,
+            addons: None,
+            delivery: None,
+            checkout_fields: None,
+            gift_cards: None,
+            flash_sales: None,
+            purchase_limits: None,
+            landed_cost: None,
+            pickup: None,
+            fraud: None,
             config,
         }
     }
 
+    /// Include the add-ons chosen on carts in checkout totals and orders
+    pub fn with_addons(mut self, addons: Arc<dyn AddonRepository>) -> Self {
+        self.addons = Some(addons);
+        self
+    }
+
+    /// Accept delivery instructions and windows, checked with `scheduler`
+    pub fn with_delivery(mut self, scheduler: DeliveryScheduler, repository: Arc<dyn DeliveryRepository>) -> Self {
+        self.delivery = Some((scheduler, repository));
+        self
+    }
+
+    /// Accept answers to the custom checkout fields in `schema`
+    pub fn with_checkout_fields(mut self, schema: CheckoutFieldSchema, repository: Arc<dyn CheckoutFieldRepository>) -> Self {
+        self.checkout_fields = Some((schema, repository));
+        self
+    }
+
+    /// Accept gift cards as payment, together with the gateway
+    pub fn with_gift_cards(mut self, gift_cards: Arc<dyn GiftCardRepository>) -> Self {
+        self.gift_cards = Some(gift_cards);
+        self
+    }
+
+    /// Require purchase tokens for items under a live flash sale in `store`
+    pub fn with_flash_sales(mut self, store: FlashSaleStore) -> Self {
+        self.flash_sales = Some(store);
+        self
+    }
+
+    /// Enforce purchase limits and allocation lists
+    pub fn with_purchase_limits(mut self, purchase_limits: PurchaseLimiter) -> Self {
+        self.purchase_limits = Some(purchase_limits);
+        self
+    }
+
+    /// Quote duties and import taxes of cross-border orders with `provider`,
+    /// using the HS codes and origins in `customs`
+    pub fn with_landed_cost(mut self, provider: Arc<dyn LandedCostProvider>, customs: Arc<dyn CustomsRepository>) -> Self {
+        self.landed_cost = Some((provider, customs));
+        self
+    }
+
+    /// Offer free pickup at the pickup locations with everything in stock
+    pub fn with_pickup(mut self, pickup: Arc<dyn PickupRepository>) -> Self {
+        self.pickup = Some(pickup);
+        self
+    }
+
+    /// Screen orders for fraud before they are paid
+    pub fn with_fraud(mut self, fraud: FraudScreen) -> Self {
+        self.fraud = Some(fraud);
+        self
+    }
+
     /// Initiate checkout - calculate totals, tax, and available shipping rates
     pub async fn initiate_checkout(
         &self,
@@ -184,10 +321,18 @@ impl CheckoutService {
 
         // Validate cart
         self.validate_cart(&cart, &items).await?;
+        let buyer = Buyer {
+            customer_id: request.customer_id.or(cart.customer_id),
+            email: cart.email.clone(),
+            household_key: Some(household_key(&request.shipping_address)),
+        };
+        self.check_purchase_limits(&items, &buyer).await?;
 
         // Calculate subtotal (already in cart)
         let subtotal = cart.subtotal;
         let discount_total = cart.discount_total;
+        let addons = self.cart_addons(cart.id).await?;
+        let addon_total = addon_total(&addons);
 
         // Validate VAT ID if provided
         let vat_id_valid = if let Some(ref vat_id) = request.vat_id {
@@ -209,6 +354,7 @@ impl CheckoutService {
         // Calculate tax
         let tax_result = self.calculate_tax(
             &items,
+            &addons,
             &request.shipping_address,
             request.billing_address.as_ref(),
             request.vat_id.as_deref(),
@@ -221,6 +367,7 @@ impl CheckoutService {
         let shipping_rates = self.get_shipping_rates(
             &request.shipping_address,
             &package,
+            &items,
         ).await?;
 
         // Calculate initial shipping cost (will be updated when customer selects rate)
@@ -231,7 +378,16 @@ impl CheckoutService {
         ).await?;
 
         let tax_total = tax_result.total_tax + shipping_tax;
-        let total = subtotal - discount_total + shipping_total + tax_total;
+        let landed_cost = self.quote_landed_cost(
+            &items,
+            &request.shipping_address,
+            shipping_total,
+            tax_total,
+            cart.currency,
+        ).await?;
+        let incoterm = choose_incoterm(landed_cost.as_ref(), request.incoterm)?;
+        let landed_cost_total = landed_cost_charge(landed_cost.as_ref(), incoterm);
+        let total = subtotal + addon_total - discount_total + shipping_total + tax_total + landed_cost_total;
 
         // Build tax breakdown
         let tax_breakdown = tax_result.calculation.tax_breakdown.iter().map(|tb| {
@@ -247,7 +403,9 @@ impl CheckoutService {
         let summary = CheckoutSummary {
             cart_id: cart.id,
             items,
+            addons,
             subtotal,
+            addon_total,
             discount_total,
             shipping_total,
             shipping_tax,
@@ -259,6 +417,9 @@ impl CheckoutService {
             selected_shipping_rate: None,
             tax_breakdown,
             vat_id_valid,
+            landed_cost,
+            incoterm,
+            landed_cost_total,
         };
 
         debug!("Checkout summary: subtotal={}, tax={}, total={}", 
@@ -293,10 +454,13 @@ impl CheckoutService {
         // Recalculate totals
         let subtotal = cart.subtotal;
         let discount_total = cart.discount_total;
+        let addons = self.cart_addons(cart.id).await?;
+        let addon_total = addon_total(&addons);
         
         // Get item tax (recalculate to ensure consistency)
         let tax_result = self.calculate_tax(
             &items,
+            &addons,
             &shipping_address,
             None, // billing address
             None, // vat_id
@@ -305,18 +469,30 @@ impl CheckoutService {
         ).await?;
 
         let tax_total = tax_result.total_tax + shipping_tax;
-        let total = subtotal - discount_total + shipping_total + tax_total;
+        let landed_cost = self.quote_landed_cost(
+            &items,
+            &shipping_address,
+            shipping_total,
+            tax_total,
+            cart.currency,
+        ).await?;
+        let incoterm = choose_incoterm(landed_cost.as_ref(), request.incoterm)?;
+        let landed_cost_total = landed_cost_charge(landed_cost.as_ref(), incoterm);
+        let total = subtotal + addon_total - discount_total + shipping_total + tax_total + landed_cost_total;
 
         // Get available shipping rates
         let shipping_rates = self.get_shipping_rates(
             &shipping_address,
             &request.package,
+            &items,
         ).await?;
 
         let summary = CheckoutSummary {
             cart_id: cart.id,
             items,
+            addons,
             subtotal,
+            addon_total,
             discount_total,
             shipping_total,
             shipping_tax,
@@ -328,6 +504,9 @@ impl CheckoutService {
             selected_shipping_rate: Some(request.shipping_rate),
             tax_breakdown: vec![], // TODO: Rebuild breakdown
             vat_id_valid: None,
+            landed_cost,
+            incoterm,
+            landed_cost_total,
         };
 
         Ok(summary)
@@ -347,10 +526,22 @@ impl CheckoutService {
 
         // Validate cart one final time
         self.validate_cart(&cart, &items).await?;
+        let addons = self.cart_addons(cart.id).await?;
+        let addon_total = addon_total(&addons);
+        let delivery = self.validate_delivery(request.delivery.clone(), &request.selected_shipping_rate)?;
+        let custom_fields = self.validate_custom_fields(request.custom_fields.clone())?;
+        let pickup_location = self.validate_pickup(&request.selected_shipping_rate, &items).await?;
+        let buyer = Buyer {
+            customer_id: request.customer_id,
+            email: Some(request.customer_email.clone()),
+            household_key: Some(household_key(&request.shipping_address)),
+        };
+        self.check_purchase_limits(&items, &buyer).await?;
 
         // Calculate final tax
         let tax_result = self.calculate_tax(
             &items,
+            &addons,
             &request.shipping_address,
             request.billing_address.as_ref(),
             request.vat_id.as_deref(),
@@ -366,7 +557,25 @@ impl CheckoutService {
         ).await?;
 
         let tax_total = tax_result.total_tax + shipping_tax;
-        let total = cart.subtotal - cart.discount_total + shipping_total + tax_total;
+
+        // Duties and import taxes, prepaid with DDP
+        let landed_cost = self.quote_landed_cost(
+            &items,
+            &request.shipping_address,
+            shipping_total,
+            tax_total,
+            cart.currency,
+        ).await?;
+        let incoterm = choose_incoterm(landed_cost.as_ref(), request.incoterm)?;
+        let (duty_total, import_tax_total) = match (&landed_cost, incoterm) {
+            (Some(quote), Some(Incoterm::Ddp)) => (quote.duties + quote.ddp_fee, quote.import_taxes),
+            _ => (Decimal::ZERO, Decimal::ZERO),
+        };
+        let total = cart.subtotal + addon_total - cart.discount_total + shipping_total + tax_total
+            + duty_total + import_tax_total;
+
+        // Split payment between gift cards and the gateway
+        let tender = self.plan_tender(&request.gift_cards, total, cart.currency).await?;
 
         // Create order items with tax
         let order_items: Vec<CreateOrderItem> = items.iter().map(|item| {
@@ -385,6 +594,16 @@ impl CheckoutService {
             }
         }).collect();
 
+        // Tax on each add-on
+        let addons: Vec<(CartAddon, Decimal)> = addons.into_iter().map(|addon| {
+            let tax = tax_result.calculation.line_items.iter()
+                .find(|li| li.item_id == addon.id)
+                .map(|li| li.tax_amount)
+                .unwrap_or_default();
+            (addon, tax)
+        }).collect();
+        let addon_tax: Decimal = addons.iter().map(|(_, tax)| *tax).sum();
+
         // Create order
         let create_order_request = CreateOrderRequest {
             customer_id: request.customer_id,
@@ -394,54 +613,336 @@ impl CheckoutService {
             items: order_items,
             currency: cart.currency.to_string(),
             subtotal: cart.subtotal,
+            addon_total,
+            addon_tax,
             tax_total,
             shipping_total,
             discount_total: cart.discount_total,
+            incoterm,
+            duty_total,
+            import_tax_total,
             total,
-            notes: request.notes,
+            notes: request.notes.clone(),
             tags: None,
             metadata: serde_json::json!({
                 "cart_id": cart.id.to_string(),
                 "vat_id": request.vat_id,
                 "shipping_carrier": request.selected_shipping_rate.carrier,
                 "shipping_service": request.selected_shipping_rate.service_code,
+                "pickup_location": pickup_location.as_ref().map(|location| &location.code),
             }),
         };
 
-        let order = self.order_service.create_order(create_order_request).await?;
+        // Claim flash sale stock; it is given back if anything below fails
+        let claims = self.claim_flash_sales(&items, &request).await?;
+
+        let placed = async {
+            let order = self.order_service.create_order(create_order_request).await?;
+
+            // Score the order before anything is spent or charged
+            let risk = self.screen_order(&order, &request).await?;
+            if risk == RiskAction::Cancel {
+                return Err(Error::validation("We couldn't place this order; please contact us to complete it"));
+            }
+
+            // Record the household so later orders to the same address count against household limits
+            if let (Some(purchase_limits), Some(household_key)) = (&self.purchase_limits, &buyer.household_key) {
+                purchase_limits.record_household(order.id, household_key).await?;
+            }
+
+            // Record the add-ons (with their instructions for the pick list)
+            if let (Some(repository), false) = (&self.addons, addons.is_empty()) {
+                repository.attach_to_order(order.id, &addons).await?;
+            }
+
+            // Record delivery preferences for the label and packing slip
+            if let (Some((_, repository)), Some(delivery)) = (&self.delivery, &delivery) {
+                repository.save_for_order(order.id, delivery).await?;
+            }
+
+            // Record where a pickup order is collected; it is packed there
+            if let (Some(repository), Some(location)) = (&self.pickup, &pickup_location) {
+                repository.set_order_location(order.id, location.id).await?;
+            }
+
+            // Record the custom checkout field answers
+            if let (Some((_, repository)), false) = (&self.checkout_fields, custom_fields.is_empty()) {
+                repository.save_for_order(order.id, &custom_fields).await?;
+            }
+
+            // Record tax transaction for reporting
+            self.tax_service.record_tax_transaction(order.id, &tax_result.calculation).await?;
+
+            // Spend the gift cards, then charge the rest through the gateway
+            self.redeem_gift_cards(&tender, order.id).await?;
+            let require_3ds = risk == RiskAction::Require3ds;
+            let payment = match self.charge_gateway(&tender, &order, &request, cart.currency, require_3ds).await {
+                Ok(payment) => payment,
+                Err(e) => {
+                    self.reverse_gift_cards(&tender.gift_cards, order.id).await;
+                    return Err(e);
+                }
+            };
+            Ok((order, payment, risk))
+        }
+        .await;
+        let (order, payment, risk) = match placed {
+            Ok(placed) => placed,
+            Err(e) => {
+                self.release_flash_sales(&claims, request.customer_id).await;
+                return Err(e);
+            }
+        };
 
-        // Record tax transaction for reporting
-        self.tax_service.record_tax_transaction(order.id, &tax_result.calculation).await?;
+        // Mark cart as converted
+        self.cart_service.mark_converted(cart.id, Some(order.id)).await?;
+
+        info!("Checkout complete: order={}, payment={:?}, gift_cards={}", order.id, payment.as_ref().map(|p| &p.id), tender.gift_card_total());
+
+        Ok(CheckoutResult {
+            order,
+            total_charged: payment.as_ref().map(|p| p.amount).unwrap_or_default(),
+            payment_id: payment.map(|p| p.id),
+            currency: cart.currency,
+            gift_cards: tender.gift_cards,
+            incoterm,
+            landed_cost_total: duty_total + import_tax_total,
+            held_for_review: risk == RiskAction::Review,
+        })
+    }
+
+    /// Fraud screening action for a new order; Allow without screening
+    async fn screen_order(&self, order: &Order, request: &CompleteCheckoutRequest) -> Result<RiskAction> {
+        let Some(fraud) = &self.fraud else {
+            return Ok(RiskAction::Allow);
+        };
+        let assessment = fraud
+            .screen(RiskContext {
+                order_id: order.id,
+                customer_id: request.customer_id,
+                email: request.customer_email.clone(),
+                client_ip: request.client_ip.clone(),
+                billing_country: request.billing_address.as_ref().map(|address| address.country.clone()),
+                shipping_country: Some(request.shipping_address.country.clone()),
+                total: order.total,
+                currency: order.currency.to_string(),
+                history: Default::default(),
+            })
+            .await?;
+        Ok(assessment.action)
+    }
+
+    /// Duties and import taxes of the items shipped to `address`; None for
+    /// domestic orders or without a landed cost provider
+    async fn quote_landed_cost(
+        &self,
+        items: &[CartItem],
+        address: &Address,
+        shipping_total: Decimal,
+        tax_total: Decimal,
+        currency: Currency,
+    ) -> Result<Option<LandedCostQuote>> {
+        let Some((provider, customs)) = &self.landed_cost else {
+            return Ok(None);
+        };
+
+        let product_ids: Vec<Uuid> = items.iter().map(|item| item.product_id).collect();
+        let customs = customs.find_many(&product_ids).await?;
+        let lines = items
+            .iter()
+            .filter(|item| item.requires_shipping)
+            .map(|item| {
+                let product = customs.iter().find(|c| c.product_id == item.product_id);
+                LandedCostLine {
+                    item_id: item.id,
+                    hs_code: product.and_then(|c| c.hs_code.clone()),
+                    origin_country: product.and_then(|c| c.origin_country.clone()),
+                    value: item.total,
+                }
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return Ok(None);
+        }
+
+        provider
+            .quote(&LandedCostRequest {
+                destination_country: address.country.clone(),
+                currency: currency.to_string(),
+                lines,
+                shipping: shipping_total,
+                // Tax charged at checkout is the destination's (e.g. collected under IOSS)
+                tax_collected: tax_total > Decimal::ZERO,
+            })
+            .await
+    }
+
+    /// Refuse items over a purchase limit or held back by an allocation list
+    async fn check_purchase_limits(&self, items: &[CartItem], buyer: &Buyer) -> Result<()> {
+        let Some(purchase_limits) = &self.purchase_limits else {
+            return Ok(());
+        };
+        let lines: Vec<PurchaseLine> = items.iter().map(PurchaseLine::from).collect();
+        purchase_limits.check(buyer, &lines, Utc::now()).await
+    }
+
+    /// Spend a purchase token on the items under each live flash sale;
+    /// returns the units claimed per sale
+    async fn claim_flash_sales(&self, items: &[CartItem], request: &CompleteCheckoutRequest) -> Result<Vec<(Uuid, i64)>> {
+        let Some(store) = &self.flash_sales else {
+            return Ok(Vec::new());
+        };
+
+        // Units per sale, as one cart can hold several variants of a sale's product
+        let mut wanted: Vec<(Uuid, i64)> = Vec::new();
+        for item in items {
+            let Some(sale_id) = store.sale_for_item(item.product_id, item.variant_id).await? else {
+                continue;
+            };
+            match wanted.iter_mut().find(|(id, _)| *id == sale_id) {
+                Some((_, quantity)) => *quantity += i64::from(item.quantity),
+                None => wanted.push((sale_id, i64::from(item.quantity))),
+            }
+        }
+        if wanted.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(customer_id) = request.customer_id else {
+            return Err(Error::validation("Sign in to buy flash sale items"));
+        };
+
+        let now = Utc::now();
+        let mut claims = Vec::with_capacity(wanted.len());
+        for (sale_id, quantity) in wanted {
+            let token = request
+                .flash_sale_tokens
+                .iter()
+                .find(|token| crate::cache::flash_sale::token_sale_id(token) == Some(sale_id));
+            let claim = match token {
+                Some(token) => store.claim(token, customer_id, quantity, now).await,
+                None => Ok(FlashSaleClaim::InvalidToken),
+            };
+            let message = match claim {
+                Ok(FlashSaleClaim::Claimed) => {
+                    claims.push((sale_id, quantity));
+                    continue;
+                }
+                Ok(FlashSaleClaim::InvalidToken) => {
+                    "A flash sale item needs a valid purchase token; enter the sale again".to_string()
+                }
+                Ok(FlashSaleClaim::LimitReached { remaining }) => {
+                    format!("You can buy {} more of this flash sale item", remaining)
+                }
+                Ok(FlashSaleClaim::SoldOut) => "A flash sale item is sold out".to_string(),
+                Ok(FlashSaleClaim::InsufficientStock { available }) => {
+                    format!("Only {} of a flash sale item are left", available)
+                }
+                Ok(FlashSaleClaim::Ended | FlashSaleClaim::Closed) => "A flash sale has ended".to_string(),
+                Err(e) => {
+                    self.release_flash_sales(&claims, Some(customer_id)).await;
+                    return Err(e.into());
+                }
+            };
+            self.release_flash_sales(&claims, Some(customer_id)).await;
+            return Err(Error::validation(message));
+        }
+        Ok(claims)
+    }
+
+    /// Give back flash sale stock claimed by a checkout that failed
+    async fn release_flash_sales(&self, claims: &[(Uuid, i64)], customer_id: Option<Uuid>) {
+        let (Some(store), Some(customer_id)) = (&self.flash_sales, customer_id) else {
+            return;
+        };
+        for (sale_id, quantity) in claims {
+            if let Err(e) = store.release(*sale_id, customer_id, *quantity).await {
+                warn!("Failed to give back {} units to flash sale {}: {}", quantity, sale_id, e);
+            }
+        }
+    }
+
+    /// Look up the gift cards entered at checkout and split `total` between them and the gateway
+    async fn plan_tender(&self, codes: &[String], total: Decimal, currency: Currency) -> Result<TenderPlan> {
+        if codes.is_empty() {
+            return TenderPlan::new(total, &currency.to_string(), &[], Utc::now());
+        }
+        let Some(repository) = &self.gift_cards else {
+            return Err(Error::validation("Gift cards are not accepted"));
+        };
+
+        let mut cards = Vec::with_capacity(codes.len());
+        for code in codes {
+            let card = repository
+                .find_by_code(&GiftCard::normalize_code(code))
+                .await?
+                .ok_or_else(|| Error::validation("Gift card not found"))?;
+            cards.push(card);
+        }
+        TenderPlan::new(total, &currency.to_string(), &cards, Utc::now())
+    }
+
+    /// Spend the planned gift card amounts on the order, giving everything
+    /// back if any card can no longer pay its share
+    async fn redeem_gift_cards(&self, tender: &TenderPlan, order_id: Uuid) -> Result<()> {
+        let Some(repository) = &self.gift_cards else {
+            return Ok(());
+        };
+
+        for (index, gift_card) in tender.gift_cards.iter().enumerate() {
+            let redeemed = repository.redeem(gift_card.gift_card_id, gift_card.amount, order_id).await;
+            if !matches!(redeemed, Ok(Some(_))) {
+                self.reverse_gift_cards(&tender.gift_cards[..index], order_id).await;
+                redeemed?;
+                return Err(Error::payment_error(format!("Gift card {} can no longer pay {}", gift_card.code, gift_card.amount)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Give back gift card redemptions for an order whose payment failed
+    async fn reverse_gift_cards(&self, gift_cards: &[GiftCardTender], order_id: Uuid) {
+        let Some(repository) = &self.gift_cards else {
+            return;
+        };
+        for gift_card in gift_cards {
+            if let Err(e) = repository.reverse(gift_card.gift_card_id, gift_card.amount, order_id).await {
+                warn!("Failed to give back {} to gift card {} for order {}: {}", gift_card.amount, gift_card.code, order_id, e);
+            }
+        }
+    }
+
+    /// Charge what the gift cards did not cover; None if they covered everything
+    async fn charge_gateway(
+        &self,
+        tender: &TenderPlan,
+        order: &Order,
+        request: &CompleteCheckoutRequest,
+        currency: Currency,
+        require_3ds: bool,
+    ) -> Result<Option<Payment>> {
+        if !tender.needs_gateway() {
+            return Ok(None);
+        }
 
-        // Process payment
         let payment_request = CreatePaymentRequest {
-            amount: total,
-            currency: cart.currency.to_string(),
+            amount: tender.gateway_amount,
+            currency: currency.to_string(),
             order_id: order.id,
             customer_id: request.customer_id,
             customer_email: request.customer_email.clone(),
-            payment_method: request.payment_method,
+            payment_method: request.payment_method.clone(),
             billing_address: request.billing_address.clone(),
             metadata: serde_json::json!({
                 "order_id": order.id.to_string(),
                 "order_number": order.order_number,
+                "gift_card_total": tender.gift_card_total().to_string(),
+                "three_d_secure": if require_3ds { "required" } else { "automatic" },
             }),
         };
 
         let payment = self.payment_gateway.create_payment(payment_request).await?;
         let confirmed_payment = self.payment_gateway.confirm_payment(&payment.id).await?;
-
-        // Mark cart as converted
-        self.cart_service.mark_converted(cart.id, Some(order.id)).await?;
-
-        info!("Checkout complete: order={}, payment={}", order.id, payment.id);
-
-        Ok(CheckoutResult {
-            order,
-            payment_id: confirmed_payment.id,
-            total_charged: confirmed_payment.amount,
-            currency: cart.currency,
-        })
+        Ok(Some(confirmed_payment))
     }
 
     /// Validate cart before checkout
@@ -464,10 +965,12 @@ impl CheckoutService {
         Ok(())
     }
 
-    /// Calculate tax for cart items
+    /// Calculate tax for cart items and add-ons
+    #[allow(clippy::too_many_arguments)]
     async fn calculate_tax(
         &self,
         items: &[CartItem],
+        addons: &[CartAddon],
         shipping_address: &Address,
         billing_address: Option<&Address>,
         vat_id: Option<&str>,
@@ -475,7 +978,7 @@ impl CheckoutService {
         currency: Currency,
     ) -> Result<TaxCalculationWithShipping> {
         // Convert cart items to taxable items
-        let taxable_items: Vec<TaxableItem> = items.iter().map(|item| TaxableItem {
+        let mut taxable_items: Vec<TaxableItem> = items.iter().map(|item| TaxableItem {
             id: item.id,
             product_id: item.product_id,
             quantity: item.quantity,
@@ -487,6 +990,19 @@ impl CheckoutService {
             sku: item.sku.clone(),
         }).collect();
 
+        // Add-ons are taxed in their own tax category
+        taxable_items.extend(addons.iter().map(|addon| TaxableItem {
+            id: addon.id,
+            product_id: addon.addon_id,
+            quantity: addon.quantity(),
+            unit_price: addon.unit_price,
+            total_price: addon.total(),
+            tax_category_id: addon.tax_category_id,
+            is_digital: false,
+            title: addon.name.clone(),
+            sku: Some(addon.code.clone()),
+        }));
+
         // Build tax context
         let tax_context = TaxContext {
             customer: CustomerTaxInfo {
@@ -554,6 +1070,7 @@ impl CheckoutService {
         &self,
         destination: &Address,
         package: &Package,
+        items: &[CartItem],
     ) -> Result<Vec<ShippingRate>> {
         // TODO: Get origin address from configuration
         let origin = Address {
@@ -591,7 +1108,11 @@ impl CheckoutService {
         // Note: In production, consider implementing Clone for ShippingProviderFactory
         // or restructuring to avoid the need for cloning
         let factory = ShippingProviderFactory::new();
-        let aggregator = crate::shipping::ShippingRateAggregator::new(factory);
+        let mut aggregator = crate::shipping::ShippingRateAggregator::new(factory);
+        if let Some(pickup) = &self.pickup {
+            let locations = pickup_locations_for(pickup.as_ref(), &pickup_lines(items)).await?;
+            aggregator = aggregator.with_pickup_locations(locations);
+        }
         let rates = aggregator.get_all_rates(&origin, destination, package, &options).await?;
 
         Ok(rates)
@@ -628,6 +1149,54 @@ impl CheckoutService {
         self.config.base_shipping_cost
     }
 
+    /// Add-ons chosen on the cart
+    /// The pickup location of a pickup rate, checked to have every item in
+    /// stock; None for rates that ship
+    async fn validate_pickup(&self, rate: &ShippingRate, items: &[CartItem]) -> Result<Option<PickupLocation>> {
+        if !is_pickup_rate(rate) {
+            return Ok(None);
+        }
+        let pickup = self
+            .pickup
+            .as_ref()
+            .ok_or_else(|| Error::validation("Pickup is not offered by this store"))?;
+        Ok(Some(pickup_location_for_rate(pickup.as_ref(), rate, &pickup_lines(items)).await?))
+    }
+
+    /// Check delivery preferences against the dispatch calendar and the
+    /// chosen carrier service
+    fn validate_delivery(
+        &self,
+        delivery: Option<DeliveryDetails>,
+        rate: &ShippingRate,
+    ) -> Result<Option<DeliveryDetails>> {
+        let Some(delivery) = delivery.filter(|delivery| !delivery.is_empty()) else {
+            return Ok(None);
+        };
+        let Some((scheduler, _)) = &self.delivery else {
+            return Err(Error::validation("Delivery preferences are not accepted"));
+        };
+        let scheduled = supports_scheduled_delivery(&self.shipping_factory, &rate.provider_id, &rate.service_code);
+        let delivery = scheduler.validate(Utc::now(), delivery, scheduled)?;
+        Ok((!delivery.is_empty()).then_some(delivery))
+    }
+
+    /// Check custom checkout field answers against the configured schema
+    fn validate_custom_fields(&self, values: CheckoutFieldValues) -> Result<CheckoutFieldValues> {
+        match &self.checkout_fields {
+            Some((schema, _)) => schema.validate(values),
+            None if values.is_empty() => Ok(values),
+            None => Err(Error::validation("Custom checkout fields are not accepted")),
+        }
+    }
+
+    async fn cart_addons(&self, cart_id: Uuid) -> Result<Vec<CartAddon>> {
+        match &self.addons {
+            Some(repository) => repository.cart_addons(cart_id).await,
+            None => Ok(vec![]),
+        }
+    }
+
     /// Validate VAT ID
     async fn validate_vat_id(&self, vat_id: &str) -> Result<bool> {
         let result = self.tax_service.validate_vat_id(vat_id).await?;
@@ -652,6 +1221,43 @@ fn address_to_tax_address(address: &Address) -> TaxAddress {
     }
 }
 
+/// The incoterm of an order quoted `landed_cost`: the customer's choice if
+/// offered, otherwise the configured default; None for domestic orders
+fn choose_incoterm(landed_cost: Option<&LandedCostQuote>, requested: Option<Incoterm>) -> Result<Option<Incoterm>> {
+    let Some(quote) = landed_cost else {
+        return Ok(None);
+    };
+    let incoterm = requested.unwrap_or(quote.default_incoterm);
+    if !quote.offers(incoterm) {
+        return Err(Error::validation(format!(
+            "{} is not offered for orders to {}",
+            incoterm, quote.destination_country
+        )));
+    }
+    Ok(Some(incoterm))
+}
+
+/// Duties, import taxes and fees charged at checkout under `incoterm`
+fn landed_cost_charge(landed_cost: Option<&LandedCostQuote>, incoterm: Option<Incoterm>) -> Decimal {
+    match (landed_cost, incoterm) {
+        (Some(quote), Some(incoterm)) => quote.charge(incoterm),
+        _ => Decimal::ZERO,
+    }
+}
+
+/// Items a pickup location must have in stock
+fn pickup_lines(items: &[CartItem]) -> Vec<PickupLine> {
+    items
+        .iter()
+        .filter(|item| item.requires_shipping)
+        .map(|item| PickupLine {
+            product_id: item.product_id,
+            variant_id: item.variant_id,
+            quantity: item.quantity,
+        })
+        .collect()
+}
+
 use rust_decimal_macros::dec;
 
 #[cfg(test)]
@@ -693,4 +1299,27 @@ mod tests {
         assert_eq!(tax_addr.postal_code, Some("10115".to_string()));
         assert_eq!(tax_addr.city, Some("Berlin".to_string()));
     }
+
+    #[test]
+    fn test_choose_incoterm() {
+        let quote = LandedCostQuote {
+            destination_country: "US".to_string(),
+            currency: "EUR".to_string(),
+            duties: dec!(12.00),
+            import_taxes: dec!(0),
+            ddp_fee: dec!(3.00),
+            lines: vec![],
+            incoterms: vec![Incoterm::Dap],
+            default_incoterm: Incoterm::Dap,
+        };
+
+        assert_eq!(choose_incoterm(None, Some(Incoterm::Ddp)).unwrap(), None);
+        assert_eq!(choose_incoterm(Some(&quote), None).unwrap(), Some(Incoterm::Dap));
+        assert!(choose_incoterm(Some(&quote), Some(Incoterm::Ddp)).is_err());
+        assert_eq!(landed_cost_charge(Some(&quote), Some(Incoterm::Dap)), dec!(0));
+
+        let quote = LandedCostQuote { incoterms: vec![Incoterm::Ddp, Incoterm::Dap], ..quote };
+        let incoterm = choose_incoterm(Some(&quote), Some(Incoterm::Ddp)).unwrap();
+        assert_eq!(landed_cost_charge(Some(&quote), incoterm), dec!(15.00));
+    }
 }
@@ -0,0 +1,189 @@
+//! Collection Service
+//!
+//! Manages manual collections, whose products are added by hand, and smart
+//! collections, whose products are the ones matching their rules when a
+//! listing is filtered by them (`ProductFilter::collection_id`).
+
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    slugify, Collection, CollectionRule, CollectionType, CreateCollectionRequest,
+    UpdateCollectionRequest, COLLECTION_SORT_ORDERS,
+};
+use crate::repository::CollectionRepository;
+use crate::{Error, Result};
+
+/// Most rules a smart collection can have
+pub const MAX_COLLECTION_RULES: usize = 60;
+
+/// Collection service
+pub struct CollectionService<R: CollectionRepository> {
+    repository: R,
+}
+
+impl<R: CollectionRepository> CollectionService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create a collection, by default with a handle from its title
+    pub async fn create_collection(&self, request: CreateCollectionRequest) -> Result<Collection> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        check_rules(request.collection_type, &request.rules)?;
+        let handle = collection_handle(request.handle.as_deref().unwrap_or(&request.title))?;
+        let sort_order = match &request.sort_order {
+            Some(sort_order) => check_sort_order(sort_order)?,
+            None if request.collection_type == CollectionType::Manual => "manual",
+            None => "created-desc",
+        };
+        self.repository.create(&request, &handle, sort_order).await
+    }
+
+    /// Update a collection
+    pub async fn update_collection(&self, id: Uuid, mut request: UpdateCollectionRequest) -> Result<Collection> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let collection = self.get_collection(id).await?;
+        if let Some(rules) = &request.rules {
+            check_rules(collection.collection_type, rules)?;
+        }
+        if let Some(sort_order) = &request.sort_order {
+            check_sort_order(sort_order)?;
+        }
+        if let Some(handle) = &request.handle {
+            request.handle = Some(collection_handle(handle)?);
+        }
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Collection not found"))
+    }
+
+    /// Get a collection
+    pub async fn get_collection(&self, id: Uuid) -> Result<Collection> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Collection not found"))
+    }
+
+    /// Get a collection by ID or handle
+    pub async fn resolve_collection(&self, id_or_handle: &str) -> Result<Collection> {
+        let collection = match Uuid::parse_str(id_or_handle) {
+            Ok(id) => self.repository.find(id).await?,
+            Err(_) => self.repository.find_by_handle(id_or_handle).await?,
+        };
+        collection.ok_or_else(|| Error::not_found("Collection not found"))
+    }
+
+    /// Collections by title; only the published ones when `published_only`
+    pub async fn list_collections(&self, published_only: bool) -> Result<Vec<Collection>> {
+        self.repository.list(published_only).await
+    }
+
+    /// Delete a collection; its products stay in the catalog
+    pub async fn delete_collection(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete(id).await? {
+            return Err(Error::not_found("Collection not found"));
+        }
+        Ok(())
+    }
+
+    /// Add products to the end of a manual collection; the number added
+    pub async fn add_products(&self, collection_id: Uuid, product_ids: &[Uuid]) -> Result<u64> {
+        self.manual_collection(collection_id).await?;
+        let mut unique = Vec::with_capacity(product_ids.len());
+        for id in product_ids {
+            if !unique.contains(id) {
+                unique.push(*id);
+            }
+        }
+        self.repository.add_products(collection_id, &unique).await
+    }
+
+    /// Remove a product from a manual collection
+    pub async fn remove_product(&self, collection_id: Uuid, product_id: Uuid) -> Result<()> {
+        self.manual_collection(collection_id).await?;
+        if !self.repository.remove_product(collection_id, product_id).await? {
+            return Err(Error::not_found("Product is not in this collection"));
+        }
+        Ok(())
+    }
+
+    async fn manual_collection(&self, id: Uuid) -> Result<Collection> {
+        let collection = self.get_collection(id).await?;
+        if collection.collection_type != CollectionType::Manual {
+            return Err(Error::validation(
+                "Products cannot be added to or removed from a smart collection; change its rules instead",
+            ));
+        }
+        Ok(collection)
+    }
+}
+
+/// Smart collections need rules; manual ones take none
+fn check_rules(collection_type: CollectionType, rules: &[CollectionRule]) -> Result<()> {
+    match collection_type {
+        CollectionType::Manual if !rules.is_empty() => {
+            Err(Error::validation("Manual collections cannot have rules"))
+        }
+        CollectionType::Smart if rules.is_empty() => {
+            Err(Error::validation("Smart collections need at least one rule"))
+        }
+        CollectionType::Smart if rules.len() > MAX_COLLECTION_RULES => Err(Error::validation(format!(
+            "Smart collections can have at most {} rules",
+            MAX_COLLECTION_RULES
+        ))),
+        _ => rules.iter().try_for_each(|rule| rule.validate().map_err(Error::validation)),
+    }
+}
+
+fn check_sort_order(sort_order: &str) -> Result<&'static str> {
+    COLLECTION_SORT_ORDERS
+        .iter()
+        .find(|order| **order == sort_order)
+        .copied()
+        .ok_or_else(|| {
+            Error::validation(format!(
+                "Unknown sort order {}; expected one of {}",
+                sort_order,
+                COLLECTION_SORT_ORDERS.join(", ")
+            ))
+        })
+}
+
+fn collection_handle(title_or_handle: &str) -> Result<String> {
+    let handle = slugify(title_or_handle);
+    if handle.is_empty() {
+        return Err(Error::validation("Collection handles need at least one letter or digit"));
+    }
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CollectionRuleField, CollectionRuleRelation};
+
+    #[test]
+    fn test_check_rules() {
+        let tag = CollectionRule::new(CollectionRuleField::Tag, CollectionRuleRelation::Equals, "summer");
+        let bad = CollectionRule::new(CollectionRuleField::Tag, CollectionRuleRelation::LessThan, "summer");
+
+        assert!(check_rules(CollectionType::Manual, &[]).is_ok());
+        assert!(check_rules(CollectionType::Manual, &[tag.clone()]).is_err());
+        assert!(check_rules(CollectionType::Smart, &[]).is_err());
+        assert!(check_rules(CollectionType::Smart, &[tag.clone()]).is_ok());
+        assert!(check_rules(CollectionType::Smart, &[tag, bad]).is_err());
+    }
+
+    #[test]
+    fn test_check_sort_order() {
+        assert_eq!(check_sort_order("price-asc").unwrap(), "price-asc");
+        assert!(check_sort_order("best-selling").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Config Bundle Service
+//!
+//! Exports a store's configuration as a [`ConfigBundle`] and imports one
+//! into another instance. Tax and shipping rule records are compared and
+//! applied like catalog promotions; email templates whose content differs
+//! are replaced; payment gateway settings live in the config file, so only
+//! the changes to make there are reported.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+
+use crate::config::PaymentConfig;
+use crate::models::{
+    BundleEmailTemplate, CatalogDiffOptions, CatalogSnapshot, ConfigBundle, ConfigImportPlan, ConfigImportResult,
+    PaymentSettings, CONFIG_BUNDLE_ENTITIES, CONFIG_BUNDLE_VERSION,
+};
+use crate::notification::{catalog, NotificationChannel};
+use crate::repository::{CatalogRepository, NotificationTemplateRepository, SaveNotificationTemplateRequest};
+use crate::services::CatalogPromotionService;
+use crate::{Error, Result};
+
+/// Config bundle service
+pub struct ConfigBundleService<C: CatalogRepository, T: NotificationTemplateRepository> {
+    catalog: CatalogPromotionService<C>,
+    templates: T,
+}
+
+impl<C: CatalogRepository, T: NotificationTemplateRepository> ConfigBundleService<C, T> {
+    pub fn new(catalog: C, templates: T) -> Self {
+        Self {
+            catalog: CatalogPromotionService::new(catalog),
+            templates,
+        }
+    }
+
+    /// This instance's configuration, with the gateway settings of `payment`
+    pub async fn export(&self, payment: &PaymentConfig) -> Result<ConfigBundle> {
+        let snapshot = self.catalog.snapshot().await?;
+        let email_templates = self
+            .templates
+            .list()
+            .await?
+            .into_iter()
+            .filter(|template| template.channel == NotificationChannel::Email)
+            .map(|template| BundleEmailTemplate {
+                name: template.name,
+                subject_template: template.subject_template,
+                body_template: template.body_template,
+                html_template: template.html_template,
+                is_active: template.is_active,
+            })
+            .collect();
+
+        Ok(ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            generated_at: Some(Utc::now()),
+            tax_categories: snapshot.tax_categories,
+            tax_zones: snapshot.tax_zones,
+            tax_rates: snapshot.tax_rates,
+            shipping_rules: snapshot.shipping_rules,
+            email_templates,
+            payment: PaymentSettings::from_config(payment),
+        })
+    }
+
+    /// What importing `bundle` would change on this instance, configured
+    /// with `payment`. Tax and shipping records missing from the bundle are
+    /// only removed with `include_deletes`; stored email templates are kept.
+    pub async fn plan(
+        &self,
+        bundle: &ConfigBundle,
+        payment: &PaymentConfig,
+        include_deletes: bool,
+    ) -> Result<ConfigImportPlan> {
+        bundle.check_version()?;
+        validate_templates(&bundle.email_templates)?;
+
+        let source = CatalogSnapshot {
+            generated_at: bundle.generated_at,
+            tax_categories: bundle.tax_categories.clone(),
+            tax_zones: bundle.tax_zones.clone(),
+            tax_rates: bundle.tax_rates.clone(),
+            shipping_rules: bundle.shipping_rules.clone(),
+            ..CatalogSnapshot::default()
+        };
+        let options = CatalogDiffOptions {
+            entities: CONFIG_BUNDLE_ENTITIES.to_vec(),
+            include_deletes,
+        };
+        let changes = self.catalog.diff(&source, &options).await?;
+
+        let stored = self.templates.list().await?;
+        let email_templates = bundle
+            .email_templates
+            .iter()
+            .filter(|template| {
+                !stored.iter().any(|record| {
+                    record.name == template.name
+                        && record.subject_template == template.subject_template
+                        && record.body_template == template.body_template
+                        && record.html_template == template.html_template
+                        && record.is_active == template.is_active
+                })
+            })
+            .map(|template| template.name.clone())
+            .collect();
+
+        Ok(ConfigImportPlan {
+            changes,
+            email_templates,
+            payment_differences: bundle.payment.differences(&PaymentSettings::from_config(payment)),
+        })
+    }
+
+    /// Apply a plan made from `bundle`
+    pub async fn import(&self, bundle: &ConfigBundle, plan: &ConfigImportPlan) -> Result<ConfigImportResult> {
+        let mut result = ConfigImportResult::default();
+        if !plan.changes.is_empty() {
+            let applied = self.catalog.apply(plan.changes.clone()).await?;
+            result.created = applied.created;
+            result.updated = applied.updated;
+            result.deleted = applied.deleted;
+        }
+
+        for name in &plan.email_templates {
+            let template = bundle
+                .email_templates
+                .iter()
+                .find(|template| &template.name == name)
+                .ok_or_else(|| Error::validation(format!("Email template '{}' is not in the bundle", name)))?;
+            self.templates
+                .save(
+                    name,
+                    SaveNotificationTemplateRequest {
+                        subject_template: template.subject_template.clone(),
+                        body_template: template.body_template.clone(),
+                        html_template: template.html_template.clone(),
+                        is_active: template.is_active,
+                    },
+                )
+                .await?;
+            result.email_templates += 1;
+        }
+
+        tracing::info!(
+            "Imported configuration bundle: {} created, {} updated, {} deleted, {} email templates",
+            result.created,
+            result.updated,
+            result.deleted,
+            result.email_templates
+        );
+        Ok(result)
+    }
+}
+
+/// Templates must be listed once and only use their type's placeholders,
+/// so a bad template fails the import before anything changes
+fn validate_templates(templates: &[BundleEmailTemplate]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for template in templates {
+        if !seen.insert(template.name.as_str()) {
+            return Err(Error::validation(format!(
+                "Email template '{}' is listed more than once",
+                template.name
+            )));
+        }
+        catalog::validate_template(
+            &template.name,
+            &[
+                template.subject_template.as_str(),
+                template.body_template.as_str(),
+                template.html_template.as_deref().unwrap_or_default(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(name: &str, body: &str) -> BundleEmailTemplate {
+        BundleEmailTemplate {
+            name: name.to_string(),
+            subject_template: "Your order".to_string(),
+            body_template: body.to_string(),
+            html_template: None,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_validate_templates() {
+        assert!(validate_templates(&[template("order_confirmation", "Thanks!")]).is_ok());
+        assert!(validate_templates(&[
+            template("order_confirmation", "Thanks!"),
+            template("order_confirmation", "Thank you!"),
+        ])
+        .is_err());
+        assert!(validate_templates(&[template("order_confirmation", "{{ not_a_variable }}")]).is_err());
+    }
+}
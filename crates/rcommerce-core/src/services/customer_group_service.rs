@@ -0,0 +1,134 @@
+//! Customer Group Service
+//!
+//! Manages customer groups, their prices and who belongs to them, and
+//! loads a customer's group pricing for `OrderCalculator` and
+//! `PriceListService::resolve_customer_price`.
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    CreateCustomerGroupRequest, CustomerGroup, CustomerGroupPrice, CustomerGroupPricing,
+    SetCustomerGroupPriceRequest, UpdateCustomerGroupRequest,
+};
+use crate::repository::CustomerGroupRepository;
+use crate::{Error, Result};
+
+/// Customer group service
+pub struct CustomerGroupService<R: CustomerGroupRepository> {
+    repository: R,
+}
+
+impl<R: CustomerGroupRepository> CustomerGroupService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create a customer group
+    pub async fn create_group(&self, request: CreateCustomerGroupRequest) -> Result<CustomerGroup> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        check_discount_percent(request.discount_percent)?;
+        self.repository.create(&request).await
+    }
+
+    /// Update a customer group
+    pub async fn update_group(&self, id: Uuid, request: UpdateCustomerGroupRequest) -> Result<CustomerGroup> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if let Some(discount_percent) = request.discount_percent {
+            check_discount_percent(discount_percent)?;
+        }
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Customer group not found"))
+    }
+
+    /// Get a customer group
+    pub async fn get_group(&self, id: Uuid) -> Result<CustomerGroup> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Customer group not found"))
+    }
+
+    /// Delete a customer group; its members keep their accounts and pay
+    /// regular prices
+    pub async fn delete_group(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete(id).await? {
+            return Err(Error::not_found("Customer group not found"));
+        }
+        Ok(())
+    }
+
+    /// Set a group's price for a product or variant, from a quantity up
+    pub async fn set_price(&self, customer_group_id: Uuid, request: SetCustomerGroupPriceRequest) -> Result<CustomerGroupPrice> {
+        if request.price < Decimal::ZERO {
+            return Err(Error::validation("Prices cannot be negative"));
+        }
+        let min_quantity = request.min_quantity.unwrap_or(1);
+        if min_quantity < 1 {
+            return Err(Error::validation("min_quantity must be at least 1"));
+        }
+        self.get_group(customer_group_id).await?;
+        self.repository
+            .set_price(customer_group_id, &request, min_quantity)
+            .await?
+            .ok_or_else(|| Error::not_found("Product or variant not found"))
+    }
+
+    /// Remove a price from a group
+    pub async fn remove_price(&self, customer_group_id: Uuid, price_id: Uuid) -> Result<()> {
+        if !self.repository.delete_price(customer_group_id, price_id).await? {
+            return Err(Error::not_found("Price not found on this customer group"));
+        }
+        Ok(())
+    }
+
+    /// Move a customer into a group, or out of theirs with None
+    pub async fn assign(&self, customer_id: Uuid, customer_group_id: Option<Uuid>) -> Result<()> {
+        if let Some(customer_group_id) = customer_group_id {
+            self.get_group(customer_group_id).await?;
+        }
+        if !self.repository.assign(customer_id, customer_group_id).await? {
+            return Err(Error::not_found("Customer not found"));
+        }
+        Ok(())
+    }
+
+    /// A customer's active group with its prices for some products; None
+    /// for customers outside any active group
+    pub async fn pricing_for_customer(&self, customer_id: Uuid, product_ids: &[Uuid]) -> Result<Option<CustomerGroupPricing>> {
+        let Some(group) = self.repository.group_of_customer(customer_id).await? else {
+            return Ok(None);
+        };
+        let prices = self.repository.prices_for_products(group.id, product_ids).await?;
+        Ok(Some(CustomerGroupPricing { group, prices }))
+    }
+}
+
+fn check_discount_percent(discount_percent: Decimal) -> Result<()> {
+    if discount_percent < Decimal::ZERO || discount_percent > Decimal::ONE_HUNDRED {
+        return Err(Error::validation("discount_percent must be between 0 and 100"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_check_discount_percent() {
+        assert!(check_discount_percent(dec!(0)).is_ok());
+        assert!(check_discount_percent(dec!(12.5)).is_ok());
+        assert!(check_discount_percent(dec!(100)).is_ok());
+        assert!(check_discount_percent(dec!(-1)).is_err());
+        assert!(check_discount_percent(dec!(100.01)).is_err());
+    }
+}
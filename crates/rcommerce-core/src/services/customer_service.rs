@@ -4,7 +4,7 @@ use uuid::Uuid;
 use crate::{
     Result, Error,
     models::{
-        Customer, Address,
+        Customer, Address, Cursor, CursorPage,
         CreateCustomerRequest, UpdateCustomerRequest, CreateAddressRequest
     },
     repository::CustomerRepository,
@@ -123,6 +123,12 @@ impl CustomerService {
         })
     }
     
+    /// A page of customers newest first, continuing after `cursor`
+    pub async fn list_customers_after(&self, cursor: Option<&Cursor>, limit: i64) -> Result<CursorPage<Customer>> {
+        let rows = self.repository.list_after(cursor, limit + 1).await?;
+        Ok(CursorPage::from_rows(rows, limit, |c| Cursor::new(c.created_at, c.id)))
+    }
+    
     /// Update customer
     pub async fn update_customer(&self, id: Uuid, request: UpdateCustomerRequest) -> Result<Customer> {
         // Check if customer exists
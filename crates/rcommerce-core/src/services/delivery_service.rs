@@ -0,0 +1,74 @@
+//! Delivery Service
+//!
+//! Offers the delivery dates and windows customers can book for a carrier
+//! service, and keeps the instructions and window chosen at checkout on the
+//! order for the label and packing slip. Windows are only offered for
+//! services that support scheduled delivery; instructions are always taken.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::repository::DeliveryRepository;
+use crate::shipping::{
+    supports_scheduled_delivery, DeliveryDetails, DeliverySchedule, DeliveryScheduler, ShippingProviderFactory,
+};
+use crate::{Error, Result};
+
+/// Delivery service
+pub struct DeliveryService<R: DeliveryRepository> {
+    repository: R,
+    scheduler: DeliveryScheduler,
+    shipping: Option<Arc<ShippingProviderFactory>>,
+}
+
+impl<R: DeliveryRepository> DeliveryService<R> {
+    pub fn new(repository: R, scheduler: DeliveryScheduler) -> Self {
+        Self {
+            repository,
+            scheduler,
+            shipping: None,
+        }
+    }
+
+    /// Look up which carrier services support scheduled delivery
+    pub fn with_shipping(mut self, shipping: Arc<ShippingProviderFactory>) -> Self {
+        self.shipping = Some(shipping);
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn scheduler(&self) -> &DeliveryScheduler {
+        &self.scheduler
+    }
+
+    fn supports_windows(&self, provider_id: &str, service_code: &str) -> bool {
+        self.shipping
+            .as_ref()
+            .is_some_and(|shipping| supports_scheduled_delivery(shipping, provider_id, service_code))
+    }
+
+    /// Dates and windows bookable now for a carrier service
+    pub fn options(&self, provider_id: &str, service_code: &str) -> DeliverySchedule {
+        self.scheduler
+            .schedule(Utc::now(), self.supports_windows(provider_id, service_code))
+    }
+
+    /// Validate delivery preferences for a carrier service
+    pub fn validate(&self, provider_id: &str, service_code: &str, delivery: DeliveryDetails) -> Result<DeliveryDetails> {
+        self.scheduler
+            .validate(Utc::now(), delivery, self.supports_windows(provider_id, service_code))
+    }
+
+    /// An order's delivery preferences
+    pub async fn for_order(&self, order_id: Uuid) -> Result<DeliveryDetails> {
+        self.repository
+            .for_order(order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))
+    }
+}
@@ -7,6 +7,8 @@
 //! - Handling subscription status changes
 //! - Managing payment recovery
 
+use std::sync::Arc;
+
 use chrono::{DateTime, Duration, Utc};
 
 use tracing::{info, warn, error};
@@ -20,6 +22,7 @@ use crate::models::{
     UpdateSubscriptionRequest, CancelSubscriptionRequest,
 };
 use crate::repository::SubscriptionRepository;
+use crate::subscriptions::{ChargeResult, SubscriptionCharger};
 // TODO: Integrate with email service when notification module is ready
 // use crate::notification::email::{EmailService, EmailTemplate};
 
@@ -38,6 +41,7 @@ pub struct DunningService<R: SubscriptionRepository> {
     repository: R,
     config: DunningConfig,
     email_service: Option<EmailService>,
+    charger: Option<Arc<dyn SubscriptionCharger>>,
 }
 
 impl<R: SubscriptionRepository + Clone> Clone for DunningService<R> {
@@ -46,6 +50,7 @@ impl<R: SubscriptionRepository + Clone> Clone for DunningService<R> {
             repository: self.repository.clone(),
             config: self.config.clone(),
             email_service: self.email_service.clone(),
+            charger: self.charger.clone(),
         }
     }
 }
@@ -57,6 +62,7 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             repository,
             config: DunningConfig::default(),
             email_service: None,
+            charger: None,
         }
     }
 
@@ -66,6 +72,7 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             repository,
             config,
             email_service: None,
+            charger: None,
         }
     }
 
@@ -75,9 +82,17 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             repository,
             config,
             email_service: Some(email_service),
+            charger: None,
         }
     }
 
+    /// Charge retries to the saved payment method (without a charger,
+    /// retries are only scheduled)
+    pub fn with_charger(mut self, charger: Arc<dyn SubscriptionCharger>) -> Self {
+        self.charger = Some(charger);
+        self
+    }
+
     /// Set email service
     pub fn set_email_service(&mut self, email_service: EmailService) {
         self.email_service = Some(email_service);
@@ -187,6 +202,7 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             .unwrap_or(7);
 
         let next_retry = Utc::now() + Duration::days(days as i64);
+        self.repository.schedule_invoice_retry(invoice_id, next_retry).await?;
 
         info!(
             "Scheduled retry for subscription {} invoice {}: attempt {} in {} days at {}",
@@ -224,6 +240,13 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             }
         }
 
+        self.attempt_retry(invoice).await
+    }
+
+    /// Charge a failed invoice again and record the outcome
+    async fn attempt_retry(&self, invoice: SubscriptionInvoice) -> Result<PaymentRecoveryResult> {
+        let invoice_id = invoice.id;
+
         // Get subscription
         let subscription = self.repository.find_by_id(invoice.subscription_id).await?
             .ok_or_else(|| Error::not_found("Subscription not found"))?;
@@ -236,26 +259,32 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             )));
         }
 
-        // Attempt payment (this would integrate with payment gateway)
-        // For now, we simulate the payment attempt
         info!(
             "Attempting payment retry for subscription {}: attempt {}/{}",
             subscription.id, invoice.failed_attempts + 1, self.config.max_retries
         );
 
-        // TODO: Integrate with actual payment gateway
-        // This is where you would call the payment gateway to retry the charge
-        
-        // For now, we return a retry scheduled result
-        // In real implementation, this would either:
-        // 1. Call process_recovery() on success
-        // 2. Call process_failed_payment() on failure
-        
-        Ok(PaymentRecoveryResult::RetryScheduled {
-            next_retry_at: Utc::now() + Duration::days(1),
-            attempt_number: invoice.failed_attempts + 1,
-            max_attempts: self.config.max_retries,
-        })
+        let Some(ref charger) = self.charger else {
+            // No gateway attached: leave the retry scheduled
+            return Ok(PaymentRecoveryResult::RetryScheduled {
+                next_retry_at: invoice.next_retry_at.unwrap_or_else(|| Utc::now() + Duration::days(1)),
+                attempt_number: invoice.failed_attempts + 1,
+                max_attempts: self.config.max_retries,
+            });
+        };
+
+        let result = charger.charge(&subscription, &invoice).await.unwrap_or_else(|e| ChargeResult::Failed {
+            error_code: None,
+            message: e.to_string(),
+        });
+        match result {
+            ChargeResult::Succeeded { payment_id } => {
+                self.process_recovery(subscription.id, invoice_id, payment_id).await
+            }
+            ChargeResult::Failed { message, .. } => {
+                self.process_failed_payment(subscription.id, invoice_id, &message).await
+            }
+        }
     }
 
     /// Cancel subscription after max retries exhausted
@@ -704,8 +733,8 @@ impl<R: SubscriptionRepository + Clone> DunningService<R> {
             )));
         }
 
-        // Execute the retry
-        self.execute_retry(invoice_id).await
+        // Execute the retry now, whenever it was scheduled
+        self.attempt_retry(invoice).await
     }
 
     /// Reset dunning state for a subscription
@@ -784,6 +813,7 @@ mod tests {
                 order_id: Uuid::new_v4(),
                 product_id: Uuid::new_v4(),
                 variant_id: None,
+                plan_id: None,
                 status: SubscriptionStatus::PastDue,
                 interval: SubscriptionInterval::Monthly,
                 interval_count: 1,
@@ -901,6 +931,10 @@ mod tests {
             Ok(())
         }
 
+        async fn schedule_invoice_retry(&self, _invoice_id: Uuid, _next_retry_at: DateTime<Utc>) -> Result<()> {
+            Ok(())
+        }
+
         async fn get_pending_invoices(&self) -> Result<Vec<SubscriptionInvoice>> {
             Ok(vec![])
         }
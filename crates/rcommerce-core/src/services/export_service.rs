@@ -0,0 +1,240 @@
+//! Export Service
+//!
+//! Dumps products, customers and orders as CSV or JSON. Rows are streamed
+//! from the database and encoded one at a time, so an export of any size
+//! runs in constant memory; the API streams the chunks as the response body
+//! and `rcommerce export` writes them to a file or stdout.
+
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::models::{ExportEntity, ExportFilter, ExportFormat};
+use crate::repository::ExportRepository;
+use crate::{Error, Result};
+
+/// Encodes export rows in one format
+#[derive(Debug, Clone, Copy)]
+pub struct ExportEncoder {
+    entity: ExportEntity,
+    format: ExportFormat,
+}
+
+impl ExportEncoder {
+    pub fn new(entity: ExportEntity, format: ExportFormat) -> Self {
+        Self { entity, format }
+    }
+
+    /// Written before the first row
+    pub fn header(&self) -> Result<Vec<u8>> {
+        match self.format {
+            ExportFormat::Csv => self.csv_record(self.entity.columns().iter().map(|column| column.to_string())),
+            ExportFormat::Json => Ok(b"[".to_vec()),
+        }
+    }
+
+    /// One row; `index` counts from 0
+    pub fn row(&self, index: u64, row: &serde_json::Value) -> Result<Vec<u8>> {
+        match self.format {
+            ExportFormat::Csv => self.csv_record(self.entity.columns().iter().map(|column| csv_cell(&row[*column]))),
+            ExportFormat::Json => {
+                let mut chunk = if index == 0 { b"\n".to_vec() } else { b",\n".to_vec() };
+                serde_json::to_writer(&mut chunk, row)
+                    .map_err(|e| Error::Other(format!("Failed to encode export row: {}", e)))?;
+                Ok(chunk)
+            }
+        }
+    }
+
+    /// Written after the last row
+    pub fn footer(&self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Csv => Vec::new(),
+            ExportFormat::Json => b"\n]\n".to_vec(),
+        }
+    }
+
+    fn csv_record(&self, cells: impl Iterator<Item = String>) -> Result<Vec<u8>> {
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+        writer
+            .write_record(cells)
+            .map_err(|e| Error::Other(format!("Failed to encode export row: {}", e)))?;
+        writer
+            .into_inner()
+            .map_err(|e| Error::Other(format!("Failed to encode export row: {}", e)))
+    }
+}
+
+/// A JSON value as a CSV cell; nested values are written as JSON
+fn csv_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        // Keep spreadsheets from running customer-entered text as a formula
+        serde_json::Value::String(s) if s.starts_with(['=', '+', '@', '\t', '\r']) => format!("'{}", s),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Export service
+pub struct ExportService<R: ExportRepository> {
+    repository: R,
+}
+
+impl<R: ExportRepository> ExportService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Check a filter makes sense for `entity`
+    pub fn validate(&self, entity: ExportEntity, filter: &ExportFilter) -> Result<()> {
+        if let (Some(since), Some(until)) = (filter.since, filter.until) {
+            if since >= until {
+                return Err(Error::validation("Export 'since' must be before 'until'"));
+            }
+        }
+        if let Some(status) = &filter.status {
+            if !entity.statuses().contains(&status.as_str()) {
+                return Err(Error::validation(format!(
+                    "Unknown {} status '{}' (expected one of: {})",
+                    entity,
+                    status,
+                    entity.statuses().join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The encoded export, chunk by chunk
+    pub fn stream<'a>(
+        &'a self,
+        entity: ExportEntity,
+        format: ExportFormat,
+        filter: &'a ExportFilter,
+    ) -> Result<BoxStream<'a, Result<Vec<u8>>>> {
+        self.validate(entity, filter)?;
+        let encoder = ExportEncoder::new(entity, format);
+
+        let rows = self
+            .repository
+            .rows(entity, filter)
+            .enumerate()
+            .map(move |(index, row)| row.and_then(|row| encoder.row(index as u64, &row)));
+        Ok(stream::once(async move { encoder.header() })
+            .chain(rows)
+            .chain(stream::once(async move { Ok(encoder.footer()) }))
+            .boxed())
+    }
+
+    /// Write the export to `writer`; returns the number of rows
+    pub async fn write_to<W: AsyncWrite + Unpin>(
+        &self,
+        entity: ExportEntity,
+        format: ExportFormat,
+        filter: &ExportFilter,
+        writer: &mut W,
+    ) -> Result<u64> {
+        self.validate(entity, filter)?;
+        let encoder = ExportEncoder::new(entity, format);
+        let io_error = |e: std::io::Error| Error::Other(format!("Failed to write export: {}", e));
+
+        writer.write_all(&encoder.header()?).await.map_err(io_error)?;
+        let mut rows = self.repository.rows(entity, filter);
+        let mut count = 0;
+        while let Some(row) = rows.next().await {
+            writer.write_all(&encoder.row(count, &row?)?).await.map_err(io_error)?;
+            count += 1;
+        }
+        writer.write_all(&encoder.footer()).await.map_err(io_error)?;
+        writer.flush().await.map_err(io_error)?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct MockRepository {
+        rows: Vec<serde_json::Value>,
+    }
+
+    impl ExportRepository for MockRepository {
+        fn rows<'a>(&'a self, _entity: ExportEntity, _filter: &'a ExportFilter) -> BoxStream<'a, Result<serde_json::Value>> {
+            stream::iter(self.rows.clone().into_iter().map(Ok)).boxed()
+        }
+    }
+
+    fn product(title: &str) -> serde_json::Value {
+        json!({"id": "p1", "title": title, "price": "12.50", "is_active": true, "weight": null})
+    }
+
+    #[tokio::test]
+    async fn test_csv_export() {
+        let service = ExportService::new(MockRepository {
+            rows: vec![product("Mug, large"), product("=HYPERLINK(\"x\")")],
+        });
+        let mut out = Vec::new();
+        let count = service
+            .write_to(ExportEntity::Products, ExportFormat::Csv, &ExportFilter::default(), &mut out)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("id,title,slug,sku,product_type,price,"));
+        assert!(lines[1].starts_with("p1,\"Mug, large\",,,,12.50,"));
+        assert!(lines[1].contains(",true,"));
+        assert!(lines[2].starts_with("p1,\"'=HYPERLINK(\"\"x\"\")\","));
+    }
+
+    #[tokio::test]
+    async fn test_json_export_streams_an_array() {
+        let service = ExportService::new(MockRepository {
+            rows: vec![product("Mug"), product("Plate")],
+        });
+        let filter = ExportFilter::default();
+        let chunks: Vec<Vec<u8>> = service
+            .stream(ExportEntity::Products, ExportFormat::Json, &filter)
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 4);
+
+        let parsed: Vec<serde_json::Value> = serde_json::from_slice(&chunks.concat()).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1]["title"], "Plate");
+
+        let empty = ExportService::new(MockRepository { rows: vec![] });
+        let mut out = Vec::new();
+        empty.write_to(ExportEntity::Orders, ExportFormat::Json, &filter, &mut out).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Vec<serde_json::Value>>(&out).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_validate_filter() {
+        let service = ExportService::new(MockRepository { rows: vec![] });
+        let status = |status: &str| ExportFilter {
+            status: Some(status.to_string()),
+            ..Default::default()
+        };
+        assert!(service.validate(ExportEntity::Orders, &status("completed")).is_ok());
+        assert!(service.validate(ExportEntity::Orders, &status("active")).is_err());
+        assert!(service.validate(ExportEntity::Products, &status("active")).is_ok());
+
+        let now = chrono::Utc::now();
+        let backwards = ExportFilter {
+            since: Some(now),
+            until: Some(now - chrono::Duration::days(1)),
+            status: None,
+        };
+        assert!(service.validate(ExportEntity::Customers, &backwards).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Flash Sale Service
+//!
+//! Defines flash sales and runs them. Starting a sale loads its stock and
+//! limits into Redis (`cache::flash_sale`); from then until it is closed,
+//! customers are admitted through the waiting room and checkout claims
+//! stock with their purchase token, all without reading Postgres. Closing a
+//! sale records what it sold and clears its Redis state.
+
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::cache::{FlashSaleEntry, FlashSaleStore};
+use crate::config::FlashSalesConfig;
+use crate::models::{
+    CreateFlashSaleRequest, FlashSale, FlashSaleAdmission, FlashSaleDetails, FlashSaleStats, FlashSaleStatus,
+};
+use crate::repository::{FlashSaleRepository, NewFlashSale};
+use crate::{Error, Result};
+
+/// Flash sale service
+pub struct FlashSaleService<R: FlashSaleRepository> {
+    repository: R,
+    store: Option<FlashSaleStore>,
+    config: FlashSalesConfig,
+}
+
+impl<R: FlashSaleRepository> FlashSaleService<R> {
+    pub fn new(repository: R, config: FlashSalesConfig) -> Self {
+        Self {
+            repository,
+            store: None,
+            config,
+        }
+    }
+
+    /// Run sales on `store`; without it sales can be defined but not started
+    pub fn with_store(mut self, store: FlashSaleStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn config(&self) -> &FlashSalesConfig {
+        &self.config
+    }
+
+    fn store(&self) -> Result<&FlashSaleStore> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| Error::Config("Flash sales need Redis (cache.redis_url)".to_string()))
+    }
+
+    /// Define a draft sale
+    pub async fn create(&self, request: CreateFlashSaleRequest, created_by: Option<Uuid>) -> Result<FlashSale> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.ends_at <= request.starts_at {
+            return Err(Error::validation("Flash sale must end after it starts"));
+        }
+        if request.ends_at <= Utc::now() {
+            return Err(Error::validation("Flash sale must end in the future"));
+        }
+
+        let sale = self
+            .repository
+            .create(&NewFlashSale {
+                name: request.name,
+                product_id: request.product_id,
+                variant_id: request.variant_id,
+                stock_limit: request.stock_limit,
+                per_customer_limit: request
+                    .per_customer_limit
+                    .unwrap_or(self.config.default_per_customer_limit as i32),
+                max_active_tokens: request
+                    .max_active_tokens
+                    .unwrap_or(self.config.default_max_active_tokens as i32),
+                starts_at: request.starts_at,
+                ends_at: request.ends_at,
+                created_by,
+            })
+            .await?;
+        tracing::info!("Created flash sale {} ({} units)", sale.id, sale.stock_limit);
+        Ok(sale)
+    }
+
+    /// Get a sale
+    pub async fn get(&self, id: Uuid) -> Result<FlashSale> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Flash sale not found"))
+    }
+
+    /// Get a sale with its live counters
+    pub async fn get_details(&self, id: Uuid) -> Result<FlashSaleDetails> {
+        let sale = self.get(id).await?;
+        let stats = match (&self.store, sale.status) {
+            (Some(store), FlashSaleStatus::Live) => store.stats(id, Utc::now()).await?,
+            _ => None,
+        };
+        Ok(FlashSaleDetails { sale, stats })
+    }
+
+    /// Sales by newest start
+    pub async fn list(&self, status: Option<FlashSaleStatus>, limit: i64) -> Result<Vec<FlashSale>> {
+        self.repository.list(status, limit).await
+    }
+
+    /// Put a draft sale live, loading it into Redis
+    pub async fn start(&self, id: Uuid) -> Result<FlashSale> {
+        let store = self.store()?;
+        let sale = self.get(id).await?;
+        if sale.status != FlashSaleStatus::Draft {
+            return Err(Error::validation("Only draft flash sales can be started"));
+        }
+        if sale.ends_at <= Utc::now() {
+            return Err(Error::validation("Flash sale has already ended"));
+        }
+        if self.repository.overlapping_live(&sale).await? {
+            return Err(Error::validation("Another live flash sale covers this product"));
+        }
+
+        store.arm(&sale).await?;
+        let live = match self.repository.transition(id, FlashSaleStatus::Draft, FlashSaleStatus::Live).await {
+            Ok(Some(live)) => live,
+            result => {
+                if let Err(e) = store.disarm(&sale).await {
+                    tracing::warn!("Failed to clear flash sale {} from Redis: {}", id, e);
+                }
+                result?;
+                return Err(Error::validation("Only draft flash sales can be started"));
+            }
+        };
+        tracing::info!("Flash sale {} is live", id);
+        Ok(live)
+    }
+
+    /// Close a live sale, recording what it sold and clearing its Redis state
+    pub async fn close(&self, id: Uuid) -> Result<FlashSale> {
+        let store = self.store()?;
+        let sale = self.get(id).await?;
+        if sale.status != FlashSaleStatus::Live {
+            return Err(Error::validation("Only live flash sales can be closed"));
+        }
+
+        let sold = match store.disarm(&sale).await? {
+            Some(sold) => sale.sold_quantity + i32::try_from(sold).unwrap_or(i32::MAX),
+            None => {
+                tracing::warn!("Flash sale {} had no Redis state; keeping its recorded sales", id);
+                sale.sold_quantity
+            }
+        };
+        let closed = self
+            .repository
+            .close(id, sold)
+            .await?
+            .ok_or_else(|| Error::validation("Only live flash sales can be closed"))?;
+        tracing::info!("Closed flash sale {} with {} sold", id, closed.sold_quantity);
+        Ok(closed)
+    }
+
+    /// Counters of a live sale, for the storefront
+    pub async fn stats(&self, id: Uuid) -> Result<FlashSaleStats> {
+        self.store()?
+            .stats(id, Utc::now())
+            .await?
+            .ok_or_else(|| Error::not_found("Flash sale is not live"))
+    }
+
+    /// Enter a live sale's waiting room
+    pub async fn enter(&self, id: Uuid, customer_id: Uuid) -> Result<FlashSaleAdmission> {
+        let entry = self.store()?.enter(id, customer_id, Utc::now()).await?;
+        Ok(match entry {
+            FlashSaleEntry::Admitted { token, expires_at } => FlashSaleAdmission::Admitted { token, expires_at },
+            FlashSaleEntry::Waiting { position } => FlashSaleAdmission::Waiting {
+                position,
+                retry_after_secs: self.config.retry_after_secs,
+            },
+            FlashSaleEntry::NotStarted { starts_at } => FlashSaleAdmission::NotStarted { starts_at },
+            FlashSaleEntry::SoldOut => FlashSaleAdmission::SoldOut,
+            FlashSaleEntry::LimitReached => FlashSaleAdmission::LimitReached,
+            FlashSaleEntry::Ended => FlashSaleAdmission::Ended,
+            FlashSaleEntry::Closed => return Err(Error::not_found("Flash sale is not live")),
+        })
+    }
+}
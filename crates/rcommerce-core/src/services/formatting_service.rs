@@ -0,0 +1,341 @@
+//! Formatting Service
+//!
+//! Per-locale and per-currency price display rules (symbol position,
+//! grouping, decimal places), so emails, invoices and API clients render
+//! "1.234,56 €" or "€1,234.56" from one place instead of hard-coding it.
+
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::config::FormattingConfig;
+
+/// Where the currency symbol goes relative to the amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolPosition {
+    Before,
+    After,
+}
+
+/// Number format conventions for a locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleFormat {
+    pub locale: String,
+    pub decimal_separator: String,
+    pub group_separator: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_spacing: bool,
+}
+
+/// Display settings for a currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyFormat {
+    pub code: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+/// Resolved display rule for a locale and currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceDisplayRule {
+    pub locale: String,
+    pub currency: String,
+    pub symbol: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_spacing: bool,
+    pub decimal_separator: String,
+    pub group_separator: String,
+    pub decimals: u32,
+}
+
+impl PriceDisplayRule {
+    /// Format an amount with the currency symbol
+    pub fn format(&self, amount: Decimal) -> String {
+        let number = self.format_number(amount.abs());
+        let space = if self.symbol_spacing { "\u{a0}" } else { "" };
+        let sign = if amount.is_sign_negative() && !self.round(amount).is_zero() { "-" } else { "" };
+
+        match self.symbol_position {
+            SymbolPosition::Before => format!("{}{}{}{}", sign, self.symbol, space, number),
+            SymbolPosition::After => format!("{}{}{}{}", sign, number, space, self.symbol),
+        }
+    }
+
+    /// Format an amount without the currency symbol
+    pub fn format_number(&self, amount: Decimal) -> String {
+        let rounded = self.round(amount);
+        let text = format!("{:.*}", self.decimals as usize, rounded.abs());
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+
+        let mut grouped = String::with_capacity(text.len() + integer.len() / 3);
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push_str(&self.group_separator);
+            }
+            grouped.push(digit);
+        }
+
+        let sign = if rounded.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+        match fraction {
+            Some(fraction) => format!("{}{}{}{}", sign, grouped, self.decimal_separator, fraction),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+
+    fn round(&self, amount: Decimal) -> Decimal {
+        amount.round_dp_with_strategy(self.decimals, RoundingStrategy::MidpointAwayFromZero)
+    }
+}
+
+/// Built-in locales: (locale, decimal separator, group separator, symbol position, spacing)
+const BUILTIN_LOCALES: &[(&str, &str, &str, SymbolPosition, bool)] = &[
+    ("en-US", ".", ",", SymbolPosition::Before, false),
+    ("en-GB", ".", ",", SymbolPosition::Before, false),
+    ("en-AU", ".", ",", SymbolPosition::Before, false),
+    ("en-CA", ".", ",", SymbolPosition::Before, false),
+    ("en-SG", ".", ",", SymbolPosition::Before, false),
+    ("fr-CA", ",", "\u{a0}", SymbolPosition::After, true),
+    ("fr-FR", ",", "\u{a0}", SymbolPosition::After, true),
+    ("de-DE", ",", ".", SymbolPosition::After, true),
+    ("de-CH", ".", "'", SymbolPosition::Before, true),
+    ("es-ES", ",", ".", SymbolPosition::After, true),
+    ("it-IT", ",", ".", SymbolPosition::After, true),
+    ("nl-NL", ",", ".", SymbolPosition::Before, true),
+    ("ja-JP", ".", ",", SymbolPosition::Before, false),
+    ("zh-CN", ".", ",", SymbolPosition::Before, false),
+    ("zh-HK", ".", ",", SymbolPosition::Before, false),
+];
+
+/// Built-in currencies: (code, symbol, decimals)
+const BUILTIN_CURRENCIES: &[(&str, &str, u32)] = &[
+    ("USD", "$", 2),
+    ("EUR", "€", 2),
+    ("GBP", "£", 2),
+    ("JPY", "¥", 0),
+    ("AUD", "A$", 2),
+    ("CAD", "CA$", 2),
+    ("CNY", "CN¥", 2),
+    ("HKD", "HK$", 2),
+    ("SGD", "S$", 2),
+];
+
+/// Price formatting service
+#[derive(Debug, Clone)]
+pub struct FormattingService {
+    default_locale: String,
+    locales: HashMap<String, LocaleFormat>,
+    currencies: HashMap<String, CurrencyFormat>,
+}
+
+impl Default for FormattingService {
+    fn default() -> Self {
+        Self::new(&FormattingConfig::default())
+    }
+}
+
+impl FormattingService {
+    /// Build the rule set from the built-ins plus configured overrides
+    pub fn new(config: &FormattingConfig) -> Self {
+        let mut locales: HashMap<String, LocaleFormat> = BUILTIN_LOCALES
+            .iter()
+            .map(|&(locale, decimal, group, position, spacing)| {
+                (
+                    locale.to_string(),
+                    LocaleFormat {
+                        locale: locale.to_string(),
+                        decimal_separator: decimal.to_string(),
+                        group_separator: group.to_string(),
+                        symbol_position: position,
+                        symbol_spacing: spacing,
+                    },
+                )
+            })
+            .collect();
+
+        for (locale, overrides) in &config.locales {
+            let format = locales.entry(locale.clone()).or_insert_with(|| LocaleFormat {
+                locale: locale.clone(),
+                decimal_separator: ".".to_string(),
+                group_separator: ",".to_string(),
+                symbol_position: SymbolPosition::Before,
+                symbol_spacing: false,
+            });
+            if let Some(ref decimal) = overrides.decimal_separator {
+                format.decimal_separator = decimal.clone();
+            }
+            if let Some(ref group) = overrides.group_separator {
+                format.group_separator = group.clone();
+            }
+            if let Some(position) = overrides.symbol_position {
+                format.symbol_position = position;
+            }
+            if let Some(spacing) = overrides.symbol_spacing {
+                format.symbol_spacing = spacing;
+            }
+        }
+
+        let mut currencies: HashMap<String, CurrencyFormat> = BUILTIN_CURRENCIES
+            .iter()
+            .map(|&(code, symbol, decimals)| {
+                (
+                    code.to_string(),
+                    CurrencyFormat {
+                        code: code.to_string(),
+                        symbol: symbol.to_string(),
+                        decimals,
+                    },
+                )
+            })
+            .collect();
+
+        for (code, overrides) in &config.currencies {
+            let code = code.to_uppercase();
+            let format = currencies.entry(code.clone()).or_insert_with(|| CurrencyFormat {
+                symbol: code.clone(),
+                code,
+                decimals: 2,
+            });
+            if let Some(ref symbol) = overrides.symbol {
+                format.symbol = symbol.clone();
+            }
+            if let Some(decimals) = overrides.decimals {
+                format.decimals = decimals;
+            }
+        }
+
+        Self {
+            default_locale: config.default_locale.clone(),
+            locales,
+            currencies,
+        }
+    }
+
+    /// Locale used when none is given
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    /// Whether a locale has display rules
+    pub fn has_locale(&self, locale: &str) -> bool {
+        self.locales.contains_key(locale)
+    }
+
+    /// All locales, sorted by tag
+    pub fn locales(&self) -> Vec<&LocaleFormat> {
+        let mut locales: Vec<&LocaleFormat> = self.locales.values().collect();
+        locales.sort_by(|a, b| a.locale.cmp(&b.locale));
+        locales
+    }
+
+    /// All currencies, sorted by code
+    pub fn currencies(&self) -> Vec<&CurrencyFormat> {
+        let mut currencies: Vec<&CurrencyFormat> = self.currencies.values().collect();
+        currencies.sort_by(|a, b| a.code.cmp(&b.code));
+        currencies
+    }
+
+    /// Find the closest locale: exact tag, then same language, then the default
+    fn resolve_locale(&self, locale: Option<&str>) -> &LocaleFormat {
+        let requested = locale.map(|l| l.replace('_', "-"));
+        if let Some(ref requested) = requested {
+            if let Some(format) = self
+                .locales
+                .values()
+                .find(|f| f.locale.eq_ignore_ascii_case(requested))
+            {
+                return format;
+            }
+
+            let language = requested.split('-').next().unwrap_or_default();
+            if let Some(format) = self
+                .locales()
+                .into_iter()
+                .find(|f| f.locale.split('-').next().is_some_and(|l| l.eq_ignore_ascii_case(language)))
+            {
+                return format;
+            }
+        }
+
+        self.locales
+            .get(&self.default_locale)
+            .or_else(|| self.locales.get("en-US"))
+            .expect("built-in locales include en-US")
+    }
+
+    /// Resolve the display rule for a locale and currency
+    pub fn rule(&self, locale: Option<&str>, currency: &str) -> PriceDisplayRule {
+        let locale = self.resolve_locale(locale);
+        let code = currency.to_uppercase();
+        let (symbol, decimals) = match self.currencies.get(&code) {
+            Some(format) => (format.symbol.clone(), format.decimals),
+            None => (code.clone(), 2),
+        };
+
+        PriceDisplayRule {
+            locale: locale.locale.clone(),
+            currency: code,
+            symbol,
+            symbol_position: locale.symbol_position,
+            symbol_spacing: locale.symbol_spacing,
+            decimal_separator: locale.decimal_separator.clone(),
+            group_separator: locale.group_separator.clone(),
+            decimals,
+        }
+    }
+
+    /// Format a price for display
+    pub fn format_price(&self, amount: Decimal, currency: &str, locale: Option<&str>) -> String {
+        self.rule(locale, currency).format(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CurrencyFormatOverride, LocaleFormatOverride};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_format_price_per_locale() {
+        let service = FormattingService::default();
+
+        assert_eq!(service.format_price(dec!(1234.56), "USD", Some("en-US")), "$1,234.56");
+        assert_eq!(service.format_price(dec!(1234.56), "EUR", Some("de-DE")), "1.234,56\u{a0}€");
+        assert_eq!(service.format_price(dec!(1234.5), "EUR", Some("fr")), "1\u{a0}234,50\u{a0}€");
+        assert_eq!(service.format_price(dec!(1234.5), "JPY", Some("ja_JP")), "¥1,235");
+        assert_eq!(service.format_price(dec!(-5), "GBP", None), "-£5.00");
+        assert_eq!(service.format_price(dec!(999999.999), "USD", None), "$1,000,000.00");
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let mut config = FormattingConfig {
+            default_locale: "de-DE".to_string(),
+            ..Default::default()
+        };
+        config.locales.insert(
+            "de-DE".to_string(),
+            LocaleFormatOverride {
+                symbol_position: Some(SymbolPosition::Before),
+                ..Default::default()
+            },
+        );
+        config.currencies.insert(
+            "chf".to_string(),
+            CurrencyFormatOverride {
+                symbol: Some("CHF".to_string()),
+                decimals: None,
+            },
+        );
+
+        let service = FormattingService::new(&config);
+        assert_eq!(service.format_price(dec!(10), "EUR", None), "€\u{a0}10,00");
+        assert_eq!(service.format_price(dec!(10), "CHF", Some("de-CH")), "CHF\u{a0}10.00");
+        assert_eq!(service.rule(Some("xx-YY"), "XYZ").symbol, "XYZ");
+    }
+}
@@ -0,0 +1,283 @@
+//! Fraud Service
+//!
+//! `FraudScreen` scores orders at checkout with a set of `RiskScorer`s and
+//! records the assessment (see `models::fraud`). The built-in scorers are
+//! configured in `[fraud]`; others, e.g. an external risk service, are added
+//! with `with_scorer`. Staff work through held orders with the review queue.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::FraudConfig;
+use crate::models::{
+    risk_score, RiskAction, RiskAssessment, RiskContext, RiskReviewItem, RiskReviewRequest, RiskReviewStatus,
+    RiskSignal,
+};
+use crate::repository::FraudRepository;
+use crate::{Error, Result};
+
+/// Scores one aspect of an order's risk
+#[async_trait]
+pub trait RiskScorer: Send + Sync {
+    /// Code of the signals it raises
+    fn code(&self) -> &'static str;
+
+    /// A signal if the order looks risky in this respect
+    async fn score(&self, context: &RiskContext) -> Result<Option<RiskSignal>>;
+}
+
+/// Many orders from one customer, email or IP address in a short window
+pub struct VelocityScorer {
+    pub max_orders: u32,
+    pub window_minutes: u32,
+    pub score: i32,
+}
+
+#[async_trait]
+impl RiskScorer for VelocityScorer {
+    fn code(&self) -> &'static str {
+        "velocity"
+    }
+
+    async fn score(&self, context: &RiskContext) -> Result<Option<RiskSignal>> {
+        let recent = context.history.recent_orders;
+        if recent <= i64::from(self.max_orders) {
+            return Ok(None);
+        }
+        Ok(Some(RiskSignal::new(
+            self.code(),
+            self.score,
+            format!("{} orders in {} minutes", recent, self.window_minutes),
+        )))
+    }
+}
+
+/// Billing and shipping addresses in different countries
+pub struct CountryMismatchScorer {
+    pub score: i32,
+}
+
+#[async_trait]
+impl RiskScorer for CountryMismatchScorer {
+    fn code(&self) -> &'static str {
+        "country_mismatch"
+    }
+
+    async fn score(&self, context: &RiskContext) -> Result<Option<RiskSignal>> {
+        let (Some(billing), Some(shipping)) = (&context.billing_country, &context.shipping_country) else {
+            return Ok(None);
+        };
+        if billing.trim().eq_ignore_ascii_case(shipping.trim()) {
+            return Ok(None);
+        }
+        Ok(Some(RiskSignal::new(
+            self.code(),
+            self.score,
+            format!("Billed in {}, shipped to {}", billing.trim().to_uppercase(), shipping.trim().to_uppercase()),
+        )))
+    }
+}
+
+/// A buyer's first order over a value
+pub struct HighValueFirstOrderScorer {
+    pub amount: Decimal,
+    pub score: i32,
+}
+
+#[async_trait]
+impl RiskScorer for HighValueFirstOrderScorer {
+    fn code(&self) -> &'static str {
+        "high_value_first_order"
+    }
+
+    async fn score(&self, context: &RiskContext) -> Result<Option<RiskSignal>> {
+        if context.history.previous_orders > 0 || context.total <= self.amount {
+            return Ok(None);
+        }
+        Ok(Some(RiskSignal::new(
+            self.code(),
+            self.score,
+            format!("First order of {} {} (over {})", context.total, context.currency, self.amount),
+        )))
+    }
+}
+
+/// Screens checkout orders and keeps the review queue
+#[derive(Clone)]
+pub struct FraudScreen {
+    repository: Arc<dyn FraudRepository>,
+    scorers: Vec<Arc<dyn RiskScorer>>,
+    config: FraudConfig,
+}
+
+impl FraudScreen {
+    /// Screen with the built-in scorers; those with no points are left out
+    pub fn new(repository: Arc<dyn FraudRepository>, config: FraudConfig) -> Self {
+        let mut scorers: Vec<Arc<dyn RiskScorer>> = Vec::new();
+        if config.velocity_score > 0 {
+            scorers.push(Arc::new(VelocityScorer {
+                max_orders: config.velocity_max_orders,
+                window_minutes: config.velocity_window_minutes,
+                score: config.velocity_score,
+            }));
+        }
+        if config.country_mismatch_score > 0 {
+            scorers.push(Arc::new(CountryMismatchScorer { score: config.country_mismatch_score }));
+        }
+        if config.high_value_score > 0 {
+            scorers.push(Arc::new(HighValueFirstOrderScorer {
+                amount: config.high_value_amount,
+                score: config.high_value_score,
+            }));
+        }
+        Self { repository, scorers, config }
+    }
+
+    /// Add a scorer
+    pub fn with_scorer(mut self, scorer: Arc<dyn RiskScorer>) -> Self {
+        self.scorers.push(scorer);
+        self
+    }
+
+    pub fn repository(&self) -> &Arc<dyn FraudRepository> {
+        &self.repository
+    }
+
+    /// Score an order and record the assessment, holding or cancelling the
+    /// order as its action says. `context.history` is filled in here.
+    pub async fn screen(&self, mut context: RiskContext) -> Result<RiskAssessment> {
+        let since = Utc::now() - Duration::minutes(i64::from(self.config.velocity_window_minutes));
+        context.history = self
+            .repository
+            .history(context.order_id, context.customer_id, &context.email, context.client_ip.as_deref(), since)
+            .await?;
+
+        // A failing scorer (e.g. an unreachable risk service) doesn't block checkout
+        let mut signals = Vec::new();
+        for scorer in &self.scorers {
+            match scorer.score(&context).await {
+                Ok(Some(signal)) => signals.push(signal),
+                Ok(None) => {}
+                Err(e) => warn!("Risk scorer {} failed for order {}: {}", scorer.code(), context.order_id, e),
+            }
+        }
+
+        let score = risk_score(&signals);
+        let action = self.config.action(score);
+        let assessment = RiskAssessment {
+            order_id: context.order_id,
+            score,
+            action,
+            signals,
+            email: context.email,
+            client_ip: context.client_ip,
+            review_status: (action == RiskAction::Review).then_some(RiskReviewStatus::Pending),
+            reviewed_by: None,
+            reviewed_at: None,
+            review_note: None,
+            created_at: Utc::now(),
+        };
+        self.repository.record(&assessment).await?;
+        if action != RiskAction::Allow {
+            info!("Order {} scored {} for fraud risk: {:?}", assessment.order_id, score, action);
+        }
+        Ok(assessment)
+    }
+
+    /// Held orders waiting for review (or those reviewed), oldest first
+    pub async fn review_queue(&self, status: RiskReviewStatus, limit: i64, offset: i64) -> Result<Vec<RiskReviewItem>> {
+        self.repository.review_queue(status, limit, offset).await
+    }
+
+    pub async fn get(&self, order_id: Uuid) -> Result<RiskAssessment> {
+        self.repository
+            .find(order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order has no risk assessment"))
+    }
+
+    /// Release a held order
+    pub async fn approve(&self, order_id: Uuid, staff_id: Uuid, request: RiskReviewRequest) -> Result<RiskAssessment> {
+        self.review(order_id, true, staff_id, request).await
+    }
+
+    /// Cancel a held order
+    pub async fn reject(&self, order_id: Uuid, staff_id: Uuid, request: RiskReviewRequest) -> Result<RiskAssessment> {
+        self.review(order_id, false, staff_id, request).await
+    }
+
+    async fn review(
+        &self,
+        order_id: Uuid,
+        approve: bool,
+        staff_id: Uuid,
+        request: RiskReviewRequest,
+    ) -> Result<RiskAssessment> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let note = request.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+        if let Some(assessment) = self.repository.review(order_id, approve, staff_id, note).await? {
+            return Ok(assessment);
+        }
+        match self.repository.find(order_id).await? {
+            Some(_) => Err(Error::validation("Order is not waiting for review")),
+            None => Err(Error::not_found("Order has no risk assessment")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RiskHistory;
+
+    fn context(total: i64, previous_orders: i64, recent_orders: i64) -> RiskContext {
+        RiskContext {
+            order_id: Uuid::new_v4(),
+            customer_id: None,
+            email: "buyer@example.com".to_string(),
+            client_ip: Some("203.0.113.7".to_string()),
+            billing_country: Some("DE".to_string()),
+            shipping_country: Some("de".to_string()),
+            total: Decimal::from(total),
+            currency: "EUR".to_string(),
+            history: RiskHistory { previous_orders, recent_orders },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_velocity_scorer() {
+        let scorer = VelocityScorer { max_orders: 3, window_minutes: 60, score: 40 };
+        assert!(scorer.score(&context(50, 5, 3)).await.unwrap().is_none());
+        let signal = scorer.score(&context(50, 5, 4)).await.unwrap().unwrap();
+        assert_eq!(signal.score, 40);
+        assert_eq!(signal.reason, "4 orders in 60 minutes");
+    }
+
+    #[tokio::test]
+    async fn test_country_mismatch_scorer() {
+        let scorer = CountryMismatchScorer { score: 25 };
+        let mut context = context(50, 0, 1);
+        assert!(scorer.score(&context).await.unwrap().is_none());
+
+        context.shipping_country = Some("NG".to_string());
+        let signal = scorer.score(&context).await.unwrap().unwrap();
+        assert_eq!(signal.reason, "Billed in DE, shipped to NG");
+
+        context.billing_country = None;
+        assert!(scorer.score(&context).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_high_value_first_order_scorer() {
+        let scorer = HighValueFirstOrderScorer { amount: Decimal::from(500), score: 30 };
+        assert!(scorer.score(&context(500, 0, 1)).await.unwrap().is_none());
+        assert!(scorer.score(&context(900, 2, 1)).await.unwrap().is_none());
+        assert_eq!(scorer.score(&context(900, 0, 1)).await.unwrap().unwrap().score, 30);
+    }
+}
@@ -0,0 +1,425 @@
+//! GeoIP Service
+//!
+//! Resolves a client's country from a CDN country header or an HTTP lookup
+//! API, and derives the storefront defaults from it: currency, display
+//! locale and the country to prefill for tax and shipping estimates.
+//! Storefronts can override the detected currency, locale and country;
+//! sanctioned-region blocking always uses the detected country.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::config::{GeoIpConfig, GeoIpProvider};
+use crate::models::Currency;
+
+/// Cached API lookups kept before the cache is cleared
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+/// Country codes CDNs use for "unknown" (XX) and Tor exit nodes (T1)
+const UNKNOWN_COUNTRIES: &[&str] = &["XX", "T1"];
+
+/// Built-in country to currency mapping for the supported currencies
+const COUNTRY_CURRENCIES: &[(&str, Currency)] = &[
+    ("US", Currency::USD),
+    ("GB", Currency::GBP),
+    ("JP", Currency::JPY),
+    ("AU", Currency::AUD),
+    ("CA", Currency::CAD),
+    ("CN", Currency::CNY),
+    ("HK", Currency::HKD),
+    ("SG", Currency::SGD),
+    // Eurozone
+    ("AT", Currency::EUR),
+    ("BE", Currency::EUR),
+    ("CY", Currency::EUR),
+    ("DE", Currency::EUR),
+    ("EE", Currency::EUR),
+    ("ES", Currency::EUR),
+    ("FI", Currency::EUR),
+    ("FR", Currency::EUR),
+    ("GR", Currency::EUR),
+    ("HR", Currency::EUR),
+    ("IE", Currency::EUR),
+    ("IT", Currency::EUR),
+    ("LT", Currency::EUR),
+    ("LU", Currency::EUR),
+    ("LV", Currency::EUR),
+    ("MT", Currency::EUR),
+    ("NL", Currency::EUR),
+    ("PT", Currency::EUR),
+    ("SI", Currency::EUR),
+    ("SK", Currency::EUR),
+];
+
+/// Built-in country to display locale mapping (locales known to the formatting service)
+const COUNTRY_LOCALES: &[(&str, &str)] = &[
+    ("US", "en-US"),
+    ("GB", "en-GB"),
+    ("IE", "en-GB"),
+    ("AU", "en-AU"),
+    ("CA", "en-CA"),
+    ("SG", "en-SG"),
+    ("FR", "fr-FR"),
+    ("BE", "fr-FR"),
+    ("LU", "fr-FR"),
+    ("DE", "de-DE"),
+    ("AT", "de-DE"),
+    ("CH", "de-CH"),
+    ("ES", "es-ES"),
+    ("IT", "it-IT"),
+    ("NL", "nl-NL"),
+    ("JP", "ja-JP"),
+    ("CN", "zh-CN"),
+    ("HK", "zh-HK"),
+];
+
+/// How a location's country was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeoSource {
+    /// CDN country header
+    Header,
+    /// HTTP lookup API
+    Api,
+    /// Nothing detected (GeoIP disabled, private IP or lookup failed)
+    Default,
+}
+
+/// Storefront choices that replace the detected values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoOverride {
+    pub country: Option<String>,
+    pub currency: Option<Currency>,
+    pub locale: Option<String>,
+}
+
+impl GeoOverride {
+    /// Encode as a cookie-safe value ("DE.EUR.de-DE", empty fields allowed)
+    pub fn to_cookie_value(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            self.country.as_deref().unwrap_or_default(),
+            self.currency.map(|c| c.to_string()).unwrap_or_default(),
+            self.locale.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Decode a cookie value written by `to_cookie_value`
+    pub fn from_cookie_value(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, '.');
+        let country = parts.next()?;
+        let currency = parts.next()?;
+        let locale = parts.next()?;
+
+        let geo = Self {
+            country: normalize_country(country),
+            currency: (!currency.is_empty()).then(|| currency.parse().ok()).flatten(),
+            locale: (!locale.is_empty() && is_locale_tag(locale)).then(|| locale.to_string()),
+        };
+        (geo != Self::default()).then_some(geo)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Resolved client location and the storefront defaults derived from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoLocation {
+    /// Country detected from the request, before any override
+    pub detected_country: Option<String>,
+    /// Country to prefill for tax and shipping estimates
+    pub country: Option<String>,
+    pub currency: Currency,
+    pub locale: String,
+    /// How the detected country was determined
+    pub source: GeoSource,
+    /// Whether a storefront override was applied
+    pub overridden: bool,
+    /// Whether the detected country is blocked
+    pub blocked: bool,
+}
+
+/// IP geolocation service
+pub struct GeoIpService {
+    config: GeoIpConfig,
+    default_locale: String,
+    default_currency: Currency,
+    country_currencies: HashMap<String, Currency>,
+    blocked_countries: Vec<String>,
+    http: reqwest::Client,
+    cache: RwLock<HashMap<IpAddr, (Option<String>, Instant)>>,
+}
+
+impl GeoIpService {
+    /// Create the service; `default_locale` is the store's display locale
+    pub fn new(config: GeoIpConfig, default_locale: &str) -> Self {
+        let mut country_currencies: HashMap<String, Currency> = COUNTRY_CURRENCIES
+            .iter()
+            .map(|&(country, currency)| (country.to_string(), currency))
+            .collect();
+        for (country, currency) in &config.country_currencies {
+            if let Ok(currency) = currency.parse() {
+                country_currencies.insert(country.to_uppercase(), currency);
+            }
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.api_timeout_ms))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            default_currency: config.default_currency.parse().unwrap_or_default(),
+            blocked_countries: config.blocked_countries.iter().map(|c| c.to_uppercase()).collect(),
+            default_locale: default_locale.to_string(),
+            country_currencies,
+            http,
+            cache: RwLock::new(HashMap::new()),
+            config,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Name of the CDN country header (header provider)
+    pub fn country_header(&self) -> Option<&str> {
+        (self.config.provider == GeoIpProvider::Header).then_some(self.config.country_header.as_str())
+    }
+
+    /// Whether a detected country is blocked
+    pub fn is_blocked(&self, country: Option<&str>) -> bool {
+        country.is_some_and(|c| self.blocked_countries.iter().any(|b| b.eq_ignore_ascii_case(c)))
+    }
+
+    /// Storefront currency for a country
+    pub fn currency_for_country(&self, country: Option<&str>) -> Currency {
+        country
+            .and_then(|c| self.country_currencies.get(&c.to_uppercase()))
+            .copied()
+            .unwrap_or(self.default_currency)
+    }
+
+    /// Display locale for a country
+    pub fn locale_for_country(&self, country: Option<&str>) -> String {
+        country
+            .and_then(|c| COUNTRY_LOCALES.iter().find(|(code, _)| code.eq_ignore_ascii_case(c)))
+            .map(|(_, locale)| locale.to_string())
+            .unwrap_or_else(|| self.default_locale.clone())
+    }
+
+    /// Detect the client country from the CDN header value or the client IP
+    pub async fn detect_country(&self, ip: Option<IpAddr>, header_country: Option<&str>) -> (Option<String>, GeoSource) {
+        if !self.config.enabled {
+            return (None, GeoSource::Default);
+        }
+
+        match self.config.provider {
+            GeoIpProvider::Header => match header_country.and_then(normalize_country) {
+                Some(country) => (Some(country), GeoSource::Header),
+                None => (None, GeoSource::Default),
+            },
+            GeoIpProvider::Api => match ip.filter(is_public) {
+                Some(ip) => match self.lookup(ip).await {
+                    Some(country) => (Some(country), GeoSource::Api),
+                    None => (None, GeoSource::Default),
+                },
+                None => (None, GeoSource::Default),
+            },
+        }
+    }
+
+    /// Resolve the location for a request, applying any storefront override
+    pub async fn resolve(
+        &self,
+        ip: Option<IpAddr>,
+        header_country: Option<&str>,
+        geo_override: Option<&GeoOverride>,
+    ) -> GeoLocation {
+        let (detected_country, source) = self.detect_country(ip, header_country).await;
+        self.locate(detected_country, source, geo_override)
+    }
+
+    /// Derive storefront defaults from a detected country and optional override
+    pub fn locate(
+        &self,
+        detected_country: Option<String>,
+        source: GeoSource,
+        geo_override: Option<&GeoOverride>,
+    ) -> GeoLocation {
+        let geo_override = geo_override.filter(|o| !o.is_empty());
+        let country = geo_override
+            .and_then(|o| o.country.clone())
+            .or_else(|| detected_country.clone());
+
+        GeoLocation {
+            currency: geo_override
+                .and_then(|o| o.currency)
+                .unwrap_or_else(|| self.currency_for_country(country.as_deref())),
+            locale: geo_override
+                .and_then(|o| o.locale.clone())
+                .unwrap_or_else(|| self.locale_for_country(country.as_deref())),
+            blocked: self.is_blocked(detected_country.as_deref()),
+            overridden: geo_override.is_some(),
+            detected_country,
+            country,
+            source,
+        }
+    }
+
+    /// Look up an IP with the HTTP provider, caching the result
+    async fn lookup(&self, ip: IpAddr) -> Option<String> {
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        if let Some((country, at)) = self.cache.read().await.get(&ip) {
+            if at.elapsed() < ttl {
+                return country.clone();
+            }
+        }
+
+        let country = match self.fetch(ip).await {
+            Ok(country) => country,
+            Err(e) => {
+                // Don't cache failures so the next request retries
+                tracing::warn!("GeoIP lookup for {} failed: {}", ip, e);
+                return None;
+            }
+        };
+
+        let mut cache = self.cache.write().await;
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(ip, (country.clone(), Instant::now()));
+        country
+    }
+
+    async fn fetch(&self, ip: IpAddr) -> Result<Option<String>, reqwest::Error> {
+        let Some(ref url) = self.config.api_url else {
+            return Ok(None);
+        };
+
+        let body: serde_json::Value = self
+            .http
+            .get(url.replace("{ip}", &ip.to_string()))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(body
+            .get(&self.config.api_country_field)
+            .and_then(|v| v.as_str())
+            .and_then(normalize_country))
+    }
+}
+
+/// Uppercase a two-letter country code, rejecting anything else
+fn normalize_country(country: &str) -> Option<String> {
+    let country = country.trim().to_uppercase();
+    (country.len() == 2
+        && country.chars().all(|c| c.is_ascii_alphabetic())
+        && !UNKNOWN_COUNTRIES.contains(&country.as_str()))
+    .then_some(country)
+}
+
+/// Accept BCP 47-style tags such as "de-DE" (letters, digits and dashes)
+fn is_locale_tag(locale: &str) -> bool {
+    locale.len() <= 16 && locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether an IP can be geolocated (not private, loopback or link-local)
+fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified()),
+        IpAddr::V6(v6) => !(v6.is_loopback() || v6.is_unspecified() || (v6.segments()[0] & 0xfe00) == 0xfc00),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> GeoIpService {
+        let mut config = GeoIpConfig {
+            enabled: true,
+            blocked_countries: vec!["kp".to_string()],
+            ..Default::default()
+        };
+        config.country_currencies.insert("ch".to_string(), "EUR".to_string());
+        GeoIpService::new(config, "en-US")
+    }
+
+    #[tokio::test]
+    async fn test_resolve_from_header() {
+        let service = service();
+
+        let geo = service.resolve(None, Some("de"), None).await;
+        assert_eq!(geo.country.as_deref(), Some("DE"));
+        assert_eq!(geo.currency, Currency::EUR);
+        assert_eq!(geo.locale, "de-DE");
+        assert_eq!(geo.source, GeoSource::Header);
+        assert!(!geo.overridden);
+        assert!(!geo.blocked);
+
+        let geo = service.resolve(None, Some("CH"), None).await;
+        assert_eq!(geo.currency, Currency::EUR);
+        assert_eq!(geo.locale, "de-CH");
+
+        let geo = service.resolve(None, Some("XX"), None).await;
+        assert_eq!(geo.country, None);
+        assert_eq!(geo.currency, Currency::USD);
+        assert_eq!(geo.locale, "en-US");
+        assert_eq!(geo.source, GeoSource::Default);
+    }
+
+    #[tokio::test]
+    async fn test_override_does_not_bypass_blocking() {
+        let service = service();
+        let geo_override = GeoOverride {
+            country: Some("US".to_string()),
+            currency: None,
+            locale: Some("en-GB".to_string()),
+        };
+
+        let geo = service.resolve(None, Some("KP"), Some(&geo_override)).await;
+        assert!(geo.blocked);
+        assert_eq!(geo.detected_country.as_deref(), Some("KP"));
+        assert_eq!(geo.country.as_deref(), Some("US"));
+        assert_eq!(geo.currency, Currency::USD);
+        assert_eq!(geo.locale, "en-GB");
+        assert_eq!(geo.source, GeoSource::Header);
+        assert!(geo.overridden);
+    }
+
+    #[test]
+    fn test_override_cookie_round_trip() {
+        let geo_override = GeoOverride {
+            country: Some("FR".to_string()),
+            currency: Some(Currency::EUR),
+            locale: None,
+        };
+        let value = geo_override.to_cookie_value();
+        assert_eq!(value, "FR.EUR.");
+        assert_eq!(GeoOverride::from_cookie_value(&value), Some(geo_override));
+
+        assert_eq!(GeoOverride::from_cookie_value(".."), None);
+        assert_eq!(GeoOverride::from_cookie_value("garbage"), None);
+        assert_eq!(GeoOverride::from_cookie_value("FRA.XYZ.<script>"), None);
+    }
+
+    #[test]
+    fn test_is_public() {
+        assert!(is_public(&"8.8.8.8".parse().unwrap()));
+        assert!(!is_public(&"10.1.2.3".parse().unwrap()));
+        assert!(!is_public(&"127.0.0.1".parse().unwrap()));
+        assert!(!is_public(&"fd00::1".parse().unwrap()));
+    }
+}
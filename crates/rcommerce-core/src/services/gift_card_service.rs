@@ -0,0 +1,180 @@
+//! Gift Card Service
+//!
+//! Issues gift cards, lets customers check a card's balance and staff
+//! correct it, and writes off the balance of cards past their expiry date.
+//! Cards are spent at checkout through the split tender in
+//! `payment::tender`, alongside a gateway payment for anything left over.
+
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::GiftCardsConfig;
+use crate::models::{
+    AdjustGiftCardRequest, GiftCard, GiftCardBalance, GiftCardDetails, GiftCardStatus, IssueGiftCardRequest,
+    NewGiftCard,
+};
+use crate::repository::GiftCardRepository;
+use crate::{Error, Result};
+
+/// Gift card service
+pub struct GiftCardService<R: GiftCardRepository> {
+    repository: R,
+    config: GiftCardsConfig,
+}
+
+impl<R: GiftCardRepository> GiftCardService<R> {
+    pub fn new(repository: R, config: GiftCardsConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn config(&self) -> &GiftCardsConfig {
+        &self.config
+    }
+
+    /// Check an amount is one of the denominations, or an allowed custom amount
+    fn check_amount(&self, amount: Decimal) -> Result<()> {
+        if self.config.denominations.contains(&amount) {
+            return Ok(());
+        }
+        if self.config.allow_custom_amount
+            && amount >= self.config.min_custom_amount
+            && amount <= self.config.max_custom_amount
+            && amount.scale() <= 2
+        {
+            return Ok(());
+        }
+
+        let denominations: Vec<String> = self.config.denominations.iter().map(|d| d.to_string()).collect();
+        Err(Error::validation(if self.config.allow_custom_amount {
+            format!(
+                "Gift card amount must be one of {} or between {} and {}",
+                denominations.join(", "),
+                self.config.min_custom_amount,
+                self.config.max_custom_amount
+            )
+        } else {
+            format!("Gift card amount must be one of {}", denominations.join(", "))
+        }))
+    }
+
+    /// Issue a gift card
+    pub async fn issue(&self, request: IssueGiftCardRequest, issued_by: Option<Uuid>) -> Result<GiftCard> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.check_amount(request.amount)?;
+
+        let currency = request
+            .currency
+            .map(|currency| currency.to_uppercase())
+            .unwrap_or_else(|| self.config.currency.clone());
+        if currency.parse::<crate::models::Currency>().is_err() {
+            return Err(Error::validation(format!("'{}' is not a supported currency", currency)));
+        }
+
+        let now = Utc::now();
+        let expires_at = request.expires_at.or_else(|| {
+            (self.config.expiry_days > 0).then(|| now + Duration::days(i64::from(self.config.expiry_days)))
+        });
+        if expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(Error::validation("Gift card expiry must be in the future"));
+        }
+
+        let card = self
+            .repository
+            .create(&NewGiftCard {
+                code: GiftCard::generate_code(),
+                currency,
+                amount: request.amount,
+                expires_at,
+                recipient_email: request.recipient_email,
+                recipient_name: request.recipient_name,
+                message: request.message,
+                note: request.note,
+                issued_by,
+            })
+            .await?;
+        tracing::info!("Issued gift card {} for {} {}", card.masked_code(), card.balance, card.currency);
+        Ok(card)
+    }
+
+    /// Get a card
+    pub async fn get(&self, id: Uuid) -> Result<GiftCard> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Gift card not found"))
+    }
+
+    /// Get a card with its balance history
+    pub async fn get_details(&self, id: Uuid) -> Result<GiftCardDetails> {
+        let gift_card = self.get(id).await?;
+        let transactions = self.repository.transactions(id).await?;
+        Ok(GiftCardDetails { gift_card, transactions })
+    }
+
+    /// Cards by newest
+    pub async fn list(&self, status: Option<GiftCardStatus>, limit: i64) -> Result<Vec<GiftCard>> {
+        self.repository.list(status, limit).await
+    }
+
+    /// Balance of a card, looked up by code
+    pub async fn balance(&self, code: &str) -> Result<GiftCardBalance> {
+        self.repository
+            .find_by_code(&GiftCard::normalize_code(code))
+            .await?
+            .map(|card| GiftCardBalance::from(&card))
+            .ok_or_else(|| Error::not_found("Gift card not found"))
+    }
+
+    /// Correct a card's balance
+    pub async fn adjust(&self, id: Uuid, request: AdjustGiftCardRequest, actor_id: Uuid) -> Result<GiftCard> {
+        if request.amount.is_zero() {
+            return Err(Error::validation("Adjustment amount must not be zero"));
+        }
+
+        let card = self.get(id).await?;
+        if card.status == GiftCardStatus::Expired {
+            return Err(Error::validation("Expired gift cards cannot be adjusted"));
+        }
+        let adjusted = self
+            .repository
+            .adjust(id, request.amount, actor_id, request.note.as_deref())
+            .await?
+            .ok_or_else(|| {
+                Error::validation(format!("Gift card balance is {} and cannot go below zero", card.balance))
+            })?;
+        tracing::info!("{} adjusted gift card {} by {}", actor_id, adjusted.masked_code(), request.amount);
+        Ok(adjusted)
+    }
+
+    /// Stop a card from being used
+    pub async fn disable(&self, id: Uuid) -> Result<GiftCard> {
+        self.set_status(id, GiftCardStatus::Disabled).await
+    }
+
+    /// Allow a disabled card to be used again
+    pub async fn enable(&self, id: Uuid) -> Result<GiftCard> {
+        self.set_status(id, GiftCardStatus::Active).await
+    }
+
+    async fn set_status(&self, id: Uuid, status: GiftCardStatus) -> Result<GiftCard> {
+        let card = self.get(id).await?;
+        if card.status == GiftCardStatus::Expired {
+            return Err(Error::validation("Gift card has expired"));
+        }
+        self.repository
+            .set_status(id, status)
+            .await?
+            .ok_or_else(|| Error::not_found("Gift card not found"))
+    }
+
+    /// Expire cards past their expiry date; returns how many expired
+    pub async fn expire_due(&self) -> Result<u64> {
+        self.repository.expire_due().await
+    }
+}
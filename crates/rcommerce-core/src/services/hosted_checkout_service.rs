@@ -0,0 +1,333 @@
+//! Hosted Checkout Service
+//!
+//! Creates gateway-hosted payment pages for an order: checkout sessions the
+//! storefront redirects customers to, and payment links staff send them.
+//! The gateway's checkout webhooks complete them: a paid checkout marks the
+//! order paid (and confirmed) and records the payment, then the payment
+//! link and any other page still open for the order are closed. Paid and
+//! failed checkouts are published as `payment.updated` events.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::config::HostedCheckoutConfig;
+use crate::events::{DomainEvent, EventBus, PaymentUpdated};
+use crate::models::{
+    CreateHostedCheckoutRequest, HostedCheckout, HostedCheckoutMode, HostedCheckoutStatus, NewHostedCheckout, Order,
+    OrderStatus, PaymentStatus,
+};
+use crate::payment::agnostic::{
+    AgnosticPaymentGateway, HostedCheckoutEvent, HostedCheckoutRequest, PaymentService, WebhookEvent, WebhookEventType,
+};
+use crate::repository::HostedCheckoutRepository;
+use crate::{Error, Result};
+
+/// Hosted checkout service
+pub struct HostedCheckoutService<R: HostedCheckoutRepository> {
+    repository: R,
+    payments: Arc<PaymentService>,
+    config: HostedCheckoutConfig,
+    events: Option<EventBus>,
+}
+
+impl<R: HostedCheckoutRepository> HostedCheckoutService<R> {
+    pub fn new(repository: R, payments: Arc<PaymentService>, config: HostedCheckoutConfig) -> Self {
+        Self {
+            repository,
+            payments,
+            config,
+            events: None,
+        }
+    }
+
+    /// Publish paid and failed checkouts as payment events
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish_payment(&self, checkout: &HostedCheckout, status: &str) {
+        if let Some(events) = &self.events {
+            events.publish(DomainEvent::PaymentUpdated(PaymentUpdated {
+                payment_id: None,
+                order_id: checkout.order_id,
+                status: status.to_string(),
+                previous_status: Some("pending".to_string()),
+                amount: checkout.amount,
+                currency: checkout.currency.clone(),
+                gateway: Some(checkout.gateway.clone()),
+            }));
+        }
+    }
+
+    /// Create a payment page for an order; with `customer_id`, only for
+    /// that customer's orders
+    pub async fn create(
+        &self,
+        order_id: Uuid,
+        customer_id: Option<Uuid>,
+        created_by: Option<Uuid>,
+        request: CreateHostedCheckoutRequest,
+    ) -> Result<HostedCheckout> {
+        let order = self
+            .repository
+            .find_order(order_id)
+            .await?
+            .filter(|order| customer_id.is_none() || order.customer_id == customer_id)
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+        check_payable(&order)?;
+
+        let allowed_hosts = &self.config.allowed_redirect_hosts;
+        let success_url = redirect_url(
+            request.success_url,
+            self.config.success_url.as_deref(),
+            allowed_hosts,
+            order_id,
+            "success_url",
+        )?
+        .ok_or_else(|| Error::validation("A success_url is required (or set payment.hosted_checkout.success_url)"))?;
+        let cancel_url = match request.mode {
+            HostedCheckoutMode::CheckoutSession => redirect_url(
+                request.cancel_url,
+                self.config.cancel_url.as_deref(),
+                allowed_hosts,
+                order_id,
+                "cancel_url",
+            )?,
+            HostedCheckoutMode::PaymentLink => None,
+        };
+
+        let gateway_id = request
+            .gateway_id
+            .unwrap_or_else(|| self.payments.default_gateway().to_string());
+        let gateway = self
+            .payments
+            .get_gateway(Some(&gateway_id))
+            .ok_or_else(|| Error::validation(format!("Gateway '{}' not found", gateway_id)))?;
+
+        let expires_at = (request.mode == HostedCheckoutMode::CheckoutSession)
+            .then(|| Utc::now() + Duration::minutes(i64::from(self.config.expires_after_mins)));
+        let currency = order.currency.to_string();
+        let page = gateway
+            .create_hosted_checkout(HostedCheckoutRequest {
+                mode: request.mode,
+                order_id,
+                amount: order.total,
+                currency: currency.clone(),
+                customer_email: (!order.email.is_empty()).then(|| order.email.clone()),
+                description: format!("Order {}", order.order_number),
+                success_url: success_url.clone(),
+                cancel_url: cancel_url.clone(),
+                expires_at,
+            })
+            .await?;
+
+        self.repository
+            .create(&NewHostedCheckout {
+                order_id,
+                gateway: gateway_id,
+                mode: request.mode,
+                provider_id: page.provider_id,
+                url: page.url,
+                amount: order.total,
+                currency,
+                success_url,
+                cancel_url,
+                expires_at: page.expires_at.or(expires_at),
+                created_by,
+            })
+            .await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<HostedCheckout> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Hosted checkout not found"))
+    }
+
+    /// An order's checkouts, newest first
+    pub async fn list_for_order(&self, order_id: Uuid) -> Result<Vec<HostedCheckout>> {
+        self.repository.list_for_order(order_id).await
+    }
+
+    /// Close an open checkout at the gateway so it can no longer be paid
+    pub async fn expire(&self, id: Uuid) -> Result<HostedCheckout> {
+        let checkout = self.get(id).await?;
+        if checkout.status != HostedCheckoutStatus::Open {
+            return Err(Error::validation("Only open checkouts can be expired"));
+        }
+        if let Some(gateway) = self.payments.get_gateway(Some(&checkout.gateway)) {
+            gateway.expire_hosted_checkout(checkout.mode, &checkout.provider_id).await?;
+        }
+        self.repository
+            .expire(id)
+            .await?
+            .ok_or_else(|| Error::validation("Only open checkouts can be expired"))
+    }
+
+    /// Apply a checkout webhook event; None when it isn't about a checkout
+    /// created here
+    pub async fn handle_webhook_event(&self, gateway_id: &str, event: &WebhookEvent) -> Result<Option<HostedCheckout>> {
+        let Some(gateway) = self.payments.get_gateway(Some(gateway_id)) else {
+            return Ok(None);
+        };
+        let Some(details) = gateway.hosted_checkout_event(event) else {
+            return Ok(None);
+        };
+
+        // Sessions opened from a payment link carry the link's ID
+        let mut checkout = self.repository.find_by_provider(gateway_id, &details.checkout_id).await?;
+        if checkout.is_none() {
+            if let Some(ref link_id) = details.link_id {
+                checkout = self.repository.find_by_provider(gateway_id, link_id).await?;
+            }
+        }
+        let Some(checkout) = checkout else {
+            tracing::info!("Ignoring {} checkout {}: not created here", gateway_id, details.checkout_id);
+            return Ok(None);
+        };
+
+        match event.event_type {
+            WebhookEventType::CheckoutCompleted => self.complete(gateway, checkout, details).await.map(Some),
+            WebhookEventType::CheckoutFailed => {
+                let failed = self.repository.fail(checkout.id, "The payment failed").await?;
+                if let Some(ref failed) = failed {
+                    self.publish_payment(failed, "failed");
+                }
+                Ok(Some(failed.unwrap_or(checkout)))
+            }
+            // Sessions opened from a payment link expire on their own; the link stays open
+            WebhookEventType::CheckoutExpired if checkout.mode == HostedCheckoutMode::PaymentLink => Ok(Some(checkout)),
+            WebhookEventType::CheckoutExpired => {
+                let expired = self.repository.expire(checkout.id).await?;
+                Ok(Some(expired.unwrap_or(checkout)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn complete(
+        &self,
+        gateway: &dyn AgnosticPaymentGateway,
+        checkout: HostedCheckout,
+        details: HostedCheckoutEvent,
+    ) -> Result<HostedCheckout> {
+        // Redelivered event
+        if checkout.status == HostedCheckoutStatus::Completed {
+            return Ok(checkout);
+        }
+        if details.amount.is_some_and(|amount| amount != checkout.amount)
+            || details
+                .currency
+                .as_ref()
+                .is_some_and(|currency| !currency.eq_ignore_ascii_case(&checkout.currency))
+        {
+            return Err(Error::payment_error(format!(
+                "Hosted checkout {} was paid with a different amount or currency than its order",
+                checkout.id
+            )));
+        }
+
+        let payment_id = details.payment_id.unwrap_or(details.checkout_id);
+        let Some(completed) = self.repository.complete(checkout.id, &payment_id).await? else {
+            return self.get(checkout.id).await;
+        };
+        self.publish_payment(&completed, "paid");
+
+        // Close the link, and any other page still open for the order
+        if completed.mode == HostedCheckoutMode::PaymentLink {
+            if let Err(e) = gateway.expire_hosted_checkout(completed.mode, &completed.provider_id).await {
+                tracing::warn!("Failed to deactivate payment link {}: {}", completed.provider_id, e);
+            }
+        }
+        for other in self.repository.list_for_order(completed.order_id).await? {
+            if other.status == HostedCheckoutStatus::Open {
+                if let Err(e) = self.expire(other.id).await {
+                    tracing::warn!("Failed to expire hosted checkout {}: {}", other.id, e);
+                }
+            }
+        }
+
+        Ok(completed)
+    }
+}
+
+/// Orders can be paid through a hosted checkout while unpaid and open
+fn check_payable(order: &Order) -> Result<()> {
+    if order.draft {
+        return Err(Error::validation("Draft orders cannot be paid"));
+    }
+    if matches!(order.payment_status, PaymentStatus::Paid | PaymentStatus::Refunded) {
+        return Err(Error::validation("Order is already paid"));
+    }
+    if matches!(order.status, OrderStatus::Cancelled | OrderStatus::Refunded | OrderStatus::Completed) {
+        return Err(Error::validation("Order cannot be paid in its current status"));
+    }
+    if order.total <= Decimal::ZERO {
+        return Err(Error::validation("Order has nothing to pay"));
+    }
+    Ok(())
+}
+
+/// A requested redirect URL, which must point at an allowed host, or else
+/// the configured one; `{order_id}` is filled in
+fn redirect_url(
+    requested: Option<String>,
+    configured: Option<&str>,
+    allowed_hosts: &[String],
+    order_id: Uuid,
+    field: &str,
+) -> Result<Option<String>> {
+    let url = match requested {
+        Some(url) => {
+            let parsed = url::Url::parse(&url)
+                .ok()
+                .filter(|parsed| matches!(parsed.scheme(), "https" | "http"))
+                .ok_or_else(|| Error::validation(format!("{} must be an http(s) URL", field)))?;
+            let host = parsed.host_str().unwrap_or_default();
+            if !allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)) {
+                return Err(Error::validation(format!(
+                    "{} must point at one of payment.hosted_checkout.allowed_redirect_hosts",
+                    field
+                )));
+            }
+            url
+        }
+        None => match configured {
+            Some(url) => url.to_string(),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(url.replace("{order_id}", &order_id.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_url() {
+        let order_id = Uuid::nil();
+        let hosts = vec!["shop.example.com".to_string()];
+
+        assert_eq!(
+            redirect_url(None, Some("https://shop.example.com/orders/{order_id}"), &[], order_id, "success_url").unwrap(),
+            Some(format!("https://shop.example.com/orders/{}", order_id))
+        );
+        assert_eq!(redirect_url(None, None, &hosts, order_id, "cancel_url").unwrap(), None);
+        assert_eq!(
+            redirect_url(Some("https://SHOP.example.com/thanks".to_string()), None, &hosts, order_id, "success_url")
+                .unwrap(),
+            Some("https://SHOP.example.com/thanks".to_string())
+        );
+
+        // Requested URLs need an allowed host
+        assert!(redirect_url(Some("https://evil.example.net/".to_string()), None, &hosts, order_id, "success_url").is_err());
+        assert!(redirect_url(Some("https://shop.example.com/".to_string()), None, &[], order_id, "success_url").is_err());
+        assert!(redirect_url(Some("javascript:alert(1)".to_string()), None, &hosts, order_id, "success_url").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Idempotency Service
+//!
+//! Decides what happens to a write request carrying an idempotency key:
+//! run it (claiming the key), replay the response of an earlier identical
+//! request, or refuse it because the key is busy or was used for something
+//! else. Keys are scoped to the caller, so two customers can use the same
+//! key without seeing each other's responses.
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::config::IdempotencyConfig;
+use crate::models::{is_valid_idempotency_key, request_fingerprint, IdempotencyOutcome, StoredResponse};
+use crate::repository::{IdempotencyClaim, IdempotencyRepository};
+use crate::{Error, Result};
+
+/// A request's idempotency key and fingerprint
+#[derive(Debug, Clone)]
+pub struct IdempotentRequest<'a> {
+    /// Caller the key belongs to (customer ID)
+    pub scope: &'a str,
+    pub key: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub body: &'a [u8],
+}
+
+/// Idempotency key service
+pub struct IdempotencyService<R: IdempotencyRepository> {
+    repository: R,
+    config: IdempotencyConfig,
+}
+
+impl<R: IdempotencyRepository> IdempotencyService<R> {
+    pub fn new(repository: R, config: IdempotencyConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn config(&self) -> &IdempotencyConfig {
+        &self.config
+    }
+
+    /// Claim the key for `request`, or say why it cannot run
+    pub async fn begin(&self, request: &IdempotentRequest<'_>, now: DateTime<Utc>) -> Result<IdempotencyOutcome> {
+        if !is_valid_idempotency_key(request.key) {
+            return Err(Error::validation("Idempotency-Key must be 1 to 255 visible ASCII characters"));
+        }
+
+        let request_hash = request_fingerprint(request.method, request.path, request.body);
+        let claim = IdempotencyClaim {
+            scope: request.scope,
+            key: request.key,
+            method: request.method,
+            path: request.path,
+            request_hash: &request_hash,
+            expires_at: now + Duration::hours(i64::from(self.config.key_ttl_hours)),
+            stale_before: now - Duration::seconds(self.config.lock_timeout_secs as i64),
+        };
+
+        // A key released or purged between the claim and the lookup can be claimed again
+        for _ in 0..2 {
+            if let Some(lock_id) = self.repository.claim(&claim).await? {
+                return Ok(IdempotencyOutcome::Started { lock_id });
+            }
+            let Some(record) = self.repository.find(request.scope, request.key).await? else {
+                continue;
+            };
+            if record.request_hash != request_hash {
+                return Ok(IdempotencyOutcome::Mismatch);
+            }
+            return Ok(match record.stored_response() {
+                Some(response) => IdempotencyOutcome::Replay(response),
+                None => IdempotencyOutcome::InProgress,
+            });
+        }
+        Ok(IdempotencyOutcome::InProgress)
+    }
+
+    /// Store the response of a started request, or release the key if the
+    /// response should not be replayed
+    pub async fn complete(&self, scope: &str, key: &str, lock_id: Uuid, response: &StoredResponse) -> Result<()> {
+        if !StoredResponse::is_replayable(response.status) {
+            self.repository.release(scope, key, lock_id).await?;
+            return Ok(());
+        }
+        if response.body.len() > self.config.max_body_bytes {
+            tracing::warn!(
+                "Response for idempotency key '{}' is {} bytes, over the {} byte limit; not storing it",
+                key,
+                response.body.len(),
+                self.config.max_body_bytes
+            );
+            self.repository.release(scope, key, lock_id).await?;
+            return Ok(());
+        }
+        if !self.repository.complete(scope, key, lock_id, response).await? {
+            tracing::warn!("Idempotency key '{}' was taken over before its request finished", key);
+        }
+        Ok(())
+    }
+
+    /// Release the key of a started request so it can be retried
+    pub async fn release(&self, scope: &str, key: &str, lock_id: Uuid) -> Result<()> {
+        self.repository.release(scope, key, lock_id).await?;
+        Ok(())
+    }
+
+    /// Delete keys past their retention; returns how many
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repository.purge_expired(now).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{IdempotencyKeyStatus, IdempotencyRecord};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockRepository {
+        keys: Mutex<HashMap<(String, String), IdempotencyRecord>>,
+    }
+
+    #[async_trait]
+    impl IdempotencyRepository for MockRepository {
+        async fn claim(&self, claim: &IdempotencyClaim<'_>) -> Result<Option<Uuid>> {
+            let mut keys = self.keys.lock().unwrap();
+            let id = (claim.scope.to_string(), claim.key.to_string());
+            if let Some(existing) = keys.get(&id) {
+                let stale = existing.status == IdempotencyKeyStatus::InProgress
+                    && existing.locked_at < claim.stale_before
+                    && existing.request_hash == claim.request_hash;
+                if existing.expires_at > Utc::now() && !stale {
+                    return Ok(None);
+                }
+            }
+            let now = Utc::now();
+            let record = IdempotencyRecord {
+                scope: claim.scope.to_string(),
+                idempotency_key: claim.key.to_string(),
+                method: claim.method.to_string(),
+                path: claim.path.to_string(),
+                request_hash: claim.request_hash.to_string(),
+                status: IdempotencyKeyStatus::InProgress,
+                lock_id: Uuid::new_v4(),
+                response_status: None,
+                response_content_type: None,
+                response_body: None,
+                created_at: now,
+                updated_at: now,
+                locked_at: now,
+                completed_at: None,
+                expires_at: claim.expires_at,
+            };
+            let lock_id = record.lock_id;
+            keys.insert(id, record);
+            Ok(Some(lock_id))
+        }
+
+        async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+            Ok(self.keys.lock().unwrap().get(&(scope.to_string(), key.to_string())).cloned())
+        }
+
+        async fn complete(&self, scope: &str, key: &str, lock_id: Uuid, response: &StoredResponse) -> Result<bool> {
+            let mut keys = self.keys.lock().unwrap();
+            match keys.get_mut(&(scope.to_string(), key.to_string())) {
+                Some(record) if record.lock_id == lock_id => {
+                    record.status = IdempotencyKeyStatus::Completed;
+                    record.response_status = Some(i32::from(response.status));
+                    record.response_content_type = response.content_type.clone();
+                    record.response_body = Some(response.body.clone());
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        async fn release(&self, scope: &str, key: &str, lock_id: Uuid) -> Result<bool> {
+            let mut keys = self.keys.lock().unwrap();
+            let id = (scope.to_string(), key.to_string());
+            if keys.get(&id).is_some_and(|record| record.lock_id == lock_id) {
+                keys.remove(&id);
+                return Ok(true);
+            }
+            Ok(false)
+        }
+
+        async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+            let mut keys = self.keys.lock().unwrap();
+            let before = keys.len();
+            keys.retain(|_, record| record.expires_at >= now);
+            Ok((before - keys.len()) as u64)
+        }
+    }
+
+    fn request<'a>(key: &'a str, body: &'a [u8]) -> IdempotentRequest<'a> {
+        IdempotentRequest { scope: "customer-1", key, method: "POST", path: "/checkout/complete", body }
+    }
+
+    fn created() -> StoredResponse {
+        StoredResponse {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"order_id":"1"}"#.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_replays_stored_response() {
+        let service = IdempotencyService::new(MockRepository::default(), IdempotencyConfig::default());
+        let now = Utc::now();
+
+        let IdempotencyOutcome::Started { lock_id } = service.begin(&request("k1", b"{}"), now).await.unwrap() else {
+            panic!("Expected the first request to start");
+        };
+        // A retry while the first request runs is told to wait
+        assert_eq!(service.begin(&request("k1", b"{}"), now).await.unwrap(), IdempotencyOutcome::InProgress);
+
+        service.complete("customer-1", "k1", lock_id, &created()).await.unwrap();
+        assert_eq!(
+            service.begin(&request("k1", b"{}"), now).await.unwrap(),
+            IdempotencyOutcome::Replay(created())
+        );
+
+        // Same key, different body
+        assert_eq!(
+            service.begin(&request("k1", br#"{"x":1}"#), now).await.unwrap(),
+            IdempotencyOutcome::Mismatch
+        );
+        // Keys are per caller
+        let other = IdempotentRequest { scope: "customer-2", ..request("k1", b"{}") };
+        assert!(matches!(service.begin(&other, now).await.unwrap(), IdempotencyOutcome::Started { .. }));
+
+        assert!(service.begin(&request("", b"{}"), now).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_release_the_key() {
+        let service = IdempotencyService::new(MockRepository::default(), IdempotencyConfig::default());
+        let now = Utc::now();
+
+        let IdempotencyOutcome::Started { lock_id } = service.begin(&request("k2", b"{}"), now).await.unwrap() else {
+            panic!("Expected the first request to start");
+        };
+        let failed = StoredResponse { status: 502, content_type: None, body: Vec::new() };
+        service.complete("customer-1", "k2", lock_id, &failed).await.unwrap();
+
+        assert!(matches!(
+            service.begin(&request("k2", b"{}"), now).await.unwrap(),
+            IdempotencyOutcome::Started { .. }
+        ));
+    }
+}
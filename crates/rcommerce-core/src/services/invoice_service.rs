@@ -0,0 +1,279 @@
+//! Invoice Service
+//!
+//! Renders order invoices as PDF documents. The layout is deliberately plain
+//! (monospaced text on A4 pages) so it can be produced without a PDF
+//! library and reads the same in every viewer.
+
+use crate::models::Invoice;
+use crate::services::FormattingService;
+
+/// Widest line that fits between the margins in 10pt Courier
+pub(crate) const LINE_WIDTH: usize = 82;
+const ITEM_TITLE_WIDTH: usize = 44;
+
+/// Invoice rendering service
+pub struct InvoiceService;
+
+impl InvoiceService {
+    /// Render an invoice as a PDF document, formatting prices for the locale
+    pub fn render_pdf(invoice: &Invoice, formatting: &FormattingService, locale: Option<&str>) -> Vec<u8> {
+        text_pdf(&Self::layout(invoice, formatting, locale))
+    }
+
+    /// Suggested download filename for an invoice
+    pub fn filename(invoice: &Invoice) -> String {
+        format!("{}.pdf", invoice.invoice_number)
+    }
+
+    /// Lay out the invoice as lines of text
+    fn layout(invoice: &Invoice, formatting: &FormattingService, locale: Option<&str>) -> Vec<String> {
+        let order = &invoice.order;
+        let rule = formatting.rule(locale, &order.currency.to_string());
+        let separator = "-".repeat(LINE_WIDTH);
+
+        let mut lines = vec![
+            format!("INVOICE {}", invoice.invoice_number),
+            String::new(),
+            format!("Order:   {}", order.order_number),
+            format!("Date:    {}", order.created_at.format("%Y-%m-%d")),
+            format!("Bill to: {}", order.email),
+            format!("Status:  {:?}", order.payment_status),
+            String::new(),
+            format!("{:>4}  {:<width$} {:>14} {:>14}", "Qty", "Item", "Unit price", "Total", width = ITEM_TITLE_WIDTH),
+            separator.clone(),
+        ];
+
+        for item in invoice.items.iter().filter(|i| !i.is_bundle_component.unwrap_or(false)) {
+            let title = match item.variant_title {
+                Some(ref variant) => format!("{} ({})", item.title, variant),
+                None => item.title.clone(),
+            };
+            lines.push(format!(
+                "{:>4}  {:<width$} {:>14} {:>14}",
+                item.quantity,
+                truncate(&title, ITEM_TITLE_WIDTH),
+                rule.format_number(item.price),
+                rule.format_number(item.subtotal),
+                width = ITEM_TITLE_WIDTH
+            ));
+        }
+
+        lines.push(separator);
+        let totals = [
+            ("Subtotal", Some(order.subtotal)),
+            ("Discount", (!order.discount_total.is_zero()).then(|| -order.discount_total)),
+            ("Shipping", Some(order.shipping_total)),
+            ("Tax", Some(order.tax_total)),
+            ("Total", Some(order.total)),
+        ];
+        for (label, amount) in totals {
+            if let Some(amount) = amount {
+                lines.push(format!("{:>width$} {:>18}", label, rule.format(amount), width = LINE_WIDTH - 19));
+            }
+        }
+
+        if !invoice.receipts.is_empty() {
+            lines.push(String::new());
+            lines.push("Payments".to_string());
+            lines.push("-".repeat(LINE_WIDTH));
+            for receipt in &invoice.receipts {
+                let date = receipt
+                    .processed_at
+                    .map(|at| at.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "pending".to_string());
+                lines.push(format!(
+                    "{}  {} via {} ({:?})",
+                    date,
+                    formatting.format_price(receipt.amount, &receipt.currency.to_string(), locale),
+                    receipt.gateway,
+                    receipt.status
+                ));
+                if let Some(ref url) = receipt.receipt_url {
+                    lines.push(format!("    Receipt: {}", truncate(url, LINE_WIDTH - 13)));
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+/// Page size, margin and text size of a PDF document, in points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PageLayout {
+    pub width: u32,
+    pub height: u32,
+    pub margin: u32,
+    pub font_size: u32,
+    pub line_height: u32,
+}
+
+impl PageLayout {
+    /// A4 with 10pt text, as invoices and reports use
+    pub(crate) const A4: PageLayout = PageLayout { width: 595, height: 842, margin: 50, font_size: 10, line_height: 14 };
+
+    /// Lines of text that fit on a page
+    pub(crate) fn lines_per_page(&self) -> usize {
+        (self.height.saturating_sub(2 * self.margin) / self.line_height).max(1) as usize
+    }
+
+    /// Characters that fit on a line (Courier glyphs are 0.6 em wide)
+    pub(crate) fn line_width(&self) -> usize {
+        (self.width.saturating_sub(2 * self.margin) * 10 / (self.font_size * 6)) as usize
+    }
+}
+
+/// Write lines of text as an A4 PDF document, as many pages as they need.
+/// Lines wider than `LINE_WIDTH` characters run off the page.
+pub(crate) fn text_pdf(lines: &[String]) -> Vec<u8> {
+    documents_pdf(&[lines.to_vec()], &PageLayout::A4).0
+}
+
+/// Write documents of text lines as one PDF, each starting on a new page;
+/// returns the PDF and its page count
+pub(crate) fn documents_pdf(documents: &[Vec<String>], layout: &PageLayout) -> (Vec<u8>, usize) {
+    let mut pages: Vec<&[String]> = documents
+        .iter()
+        .filter(|lines| !lines.is_empty())
+        .flat_map(|lines| lines.chunks(layout.lines_per_page()))
+        .collect();
+    if pages.is_empty() {
+        pages.push(&[]);
+    }
+    (write_pdf(&pages, layout), pages.len())
+}
+
+/// Truncate to a number of characters, marking the cut with "..."
+pub(crate) fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(3)).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+/// Escape text for a PDF literal string. The standard fonts use
+/// WinAnsiEncoding, so currency symbols and non-breaking spaces are written
+/// as octal codes and anything else outside ASCII is replaced.
+fn escape_pdf_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '€' => escaped.push_str("\\200"),
+            '\u{a0}' => escaped.push_str("\\240"),
+            '£' => escaped.push_str("\\243"),
+            '¥' => escaped.push_str("\\245"),
+            c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// Write pages of text lines as a PDF 1.4 document
+fn write_pdf(pages: &[&[String]], layout: &PageLayout) -> Vec<u8> {
+    // Object layout: 1 catalog, 2 page tree, 3 font, then a page and a
+    // content stream object for each page.
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + i * 2).collect();
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+
+    for (page, &page_id) in pages.iter().zip(&page_ids) {
+        let mut content = format!(
+            "BT\n/F1 {} Tf\n{} TL\n{} {} Td\n",
+            layout.font_size,
+            layout.line_height,
+            layout.margin,
+            layout.height - layout.margin
+        );
+        for line in page.iter() {
+            content.push_str(&format!("({}) Tj T*\n", escape_pdf_text(line)));
+        }
+        content.push_str("ET");
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            layout.width,
+            layout.height,
+            page_id + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = pdf.len();
+    pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_pdf_text() {
+        assert_eq!(escape_pdf_text("Mug (large) \\ 1"), "Mug \\(large\\) \\\\ 1");
+        assert_eq!(escape_pdf_text("Café"), "Caf?");
+        assert_eq!(escape_pdf_text("1.234,56\u{a0}€"), "1.234,56\\240\\200");
+    }
+
+    #[test]
+    fn test_write_pdf_xref_offsets() {
+        let lines = ["INVOICE INV-1001".to_string(), "Total 10.00 USD".to_string()];
+        let pdf = String::from_utf8(write_pdf(&[&lines[..]], &PageLayout::A4)).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("(INVOICE INV-1001) Tj"));
+
+        // Every xref entry must point at the start of its object
+        let xref = pdf.rfind("xref\n").unwrap();
+        for (i, entry) in pdf[xref..].lines().skip(3).take(5).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn test_documents_pdf_pages() {
+        let layout = PageLayout { width: 288, height: 432, margin: 12, font_size: 9, line_height: 12 };
+        assert_eq!(PageLayout::A4.line_width(), LINE_WIDTH);
+        assert_eq!(layout.lines_per_page(), 34);
+
+        // Each document starts a page; long ones run onto more
+        let label = vec!["SHIP TO".to_string(); 3];
+        let slip = vec!["1 x Mug".to_string(); 40];
+        let (pdf, pages) = documents_pdf(&[label, slip], &layout);
+        let pdf = String::from_utf8(pdf).unwrap();
+        assert_eq!(pages, 3);
+        assert!(pdf.contains("/Count 3"));
+        assert!(pdf.contains("/MediaBox [0 0 288 432]"));
+    }
+}
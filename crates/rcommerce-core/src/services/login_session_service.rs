@@ -0,0 +1,358 @@
+//! Login Session Service
+//!
+//! Issues and rotates the refresh tokens of login sessions. Every refresh
+//! swaps the token for a new one; the old token presented again means it
+//! was copied, so the whole session is revoked and both holders must log in
+//! again. A session ends `refresh_token_idle_days` after its last refresh,
+//! and never later than `refresh_token_ttl_days` after login.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::config::JwtConfig;
+use crate::models::{hash_refresh_token, LoginDevice, LoginSession, REFRESH_TOKEN_PREFIX};
+use crate::repository::LoginSessionRepository;
+use crate::services::{AuthService, JwtClaims};
+use crate::{Error, Result};
+
+/// Random characters after the prefix of a refresh token
+const REFRESH_TOKEN_LENGTH: usize = 48;
+
+/// Revoked and expired sessions are kept this long before being purged
+const PURGE_AFTER_DAYS: i64 = 7;
+
+/// A session and its new refresh token; the token is only known here
+#[derive(Debug, Clone)]
+pub struct IssuedRefreshToken {
+    pub session: LoginSession,
+    pub refresh_token: String,
+}
+
+/// Login session service
+pub struct LoginSessionService<R: LoginSessionRepository> {
+    repository: R,
+    config: JwtConfig,
+}
+
+impl<R: LoginSessionRepository> LoginSessionService<R> {
+    pub fn new(repository: R, config: JwtConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Start a session for a customer who just logged in
+    pub async fn start(&self, customer_id: Uuid, device: &LoginDevice, now: DateTime<Utc>) -> Result<IssuedRefreshToken> {
+        let refresh_token = generate_refresh_token();
+        let expires_at = self.expiry(now, now);
+        let session = self
+            .repository
+            .create(customer_id, &hash_refresh_token(&refresh_token), device, expires_at)
+            .await?;
+        Ok(IssuedRefreshToken { session, refresh_token })
+    }
+
+    /// Swap a refresh token for a new one
+    pub async fn refresh(&self, refresh_token: &str, now: DateTime<Utc>) -> Result<IssuedRefreshToken> {
+        let token_hash = hash_refresh_token(refresh_token);
+        let session = self
+            .repository
+            .find_by_token_hash(&token_hash)
+            .await?
+            .ok_or_else(|| Error::unauthorized("Invalid refresh token"))?;
+
+        if session.refresh_token_hash != token_hash {
+            // A token that was already rotated away: someone else holds a copy
+            if session.revoked_at.is_none() {
+                self.repository
+                    .revoke(session.id, session.customer_id, "refresh token reused")
+                    .await?;
+                tracing::warn!(
+                    "Refresh token of session {} was reused; revoked the session of customer {}",
+                    session.id,
+                    session.customer_id
+                );
+            }
+            return Err(Error::unauthorized("Invalid refresh token"));
+        }
+        if !session.is_active(now) {
+            return Err(Error::unauthorized("Session has ended; please log in again"));
+        }
+
+        let new_token = generate_refresh_token();
+        let expires_at = self.expiry(session.created_at, now);
+        let session = self
+            .repository
+            .rotate(session.id, &token_hash, &hash_refresh_token(&new_token), now, expires_at)
+            .await?
+            .ok_or_else(|| Error::unauthorized("Invalid refresh token"))?;
+        Ok(IssuedRefreshToken { session, refresh_token: new_token })
+    }
+
+    /// The active session whose current refresh token this is
+    pub async fn refresh_session_id(&self, refresh_token: &str, now: DateTime<Utc>) -> Result<Option<Uuid>> {
+        let token_hash = hash_refresh_token(refresh_token);
+        Ok(self
+            .repository
+            .find_by_token_hash(&token_hash)
+            .await?
+            .filter(|session| session.refresh_token_hash == token_hash && session.is_active(now))
+            .map(|session| session.id))
+    }
+
+    /// Whether a session is neither revoked nor expired; access tokens
+    /// issued for it are only accepted while it is
+    pub async fn is_active(&self, session_id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        Ok(self.repository.find_by_id(session_id).await?.is_some_and(|session| session.is_active(now)))
+    }
+
+    /// Verify an access token, rejecting tokens of ended login sessions;
+    /// every route that accepts bearer tokens authenticates through this
+    pub async fn verify_access_token(&self, auth: &AuthService, token: &str, now: DateTime<Utc>) -> Result<JwtClaims> {
+        let claims = auth.verify_token(token).map_err(|_| Error::unauthorized("Invalid access token"))?;
+        if let Some(sid) = claims.sid {
+            if !self.is_active(sid, now).await? {
+                return Err(Error::unauthorized("Login session has ended"));
+            }
+        }
+        Ok(claims)
+    }
+
+    /// A customer's signed-in devices
+    pub async fn list(&self, customer_id: Uuid, now: DateTime<Utc>) -> Result<Vec<LoginSession>> {
+        self.repository.list_active(customer_id, now).await
+    }
+
+    /// Sign out one device
+    pub async fn revoke(&self, customer_id: Uuid, session_id: Uuid, reason: &str) -> Result<()> {
+        if !self.repository.revoke(session_id, customer_id, reason).await? {
+            return Err(Error::not_found(format!("Session {} not found", session_id)));
+        }
+        Ok(())
+    }
+
+    /// Sign out every device; returns how many sessions ended
+    pub async fn revoke_all(&self, customer_id: Uuid, reason: &str) -> Result<u64> {
+        self.repository.revoke_all(customer_id, reason).await
+    }
+
+    /// Delete sessions that ended a while ago; returns how many
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repository.purge(now - Duration::days(PURGE_AFTER_DAYS)).await
+    }
+
+    /// The idle timeout from now, capped at the absolute lifetime from login
+    fn expiry(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let idle = now + Duration::days(self.config.refresh_token_idle_days as i64);
+        let absolute = created_at + Duration::days(self.config.refresh_token_ttl_days as i64);
+        idle.min(absolute)
+    }
+}
+
+fn generate_refresh_token() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(REFRESH_TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    format!("{}{}", REFRESH_TOKEN_PREFIX, secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockRepository {
+        sessions: Mutex<Vec<LoginSession>>,
+    }
+
+    #[async_trait]
+    impl LoginSessionRepository for MockRepository {
+        async fn create(
+            &self,
+            customer_id: Uuid,
+            token_hash: &str,
+            device: &LoginDevice,
+            expires_at: DateTime<Utc>,
+        ) -> Result<LoginSession> {
+            let now = Utc::now();
+            let session = LoginSession {
+                id: Uuid::new_v4(),
+                customer_id,
+                refresh_token_hash: token_hash.to_string(),
+                previous_token_hash: None,
+                user_agent: device.user_agent.clone(),
+                ip_address: device.ip_address.clone(),
+                created_at: now,
+                last_used_at: now,
+                expires_at,
+                revoked_at: None,
+                revoked_reason: None,
+            };
+            self.sessions.lock().unwrap().push(session.clone());
+            Ok(session)
+        }
+
+        async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<LoginSession>> {
+            Ok(self.sessions.lock().unwrap().iter().find(|session| {
+                session.refresh_token_hash == token_hash || session.previous_token_hash.as_deref() == Some(token_hash)
+            }).cloned())
+        }
+
+        async fn find_by_id(&self, id: Uuid) -> Result<Option<LoginSession>> {
+            Ok(self.sessions.lock().unwrap().iter().find(|session| session.id == id).cloned())
+        }
+
+        async fn rotate(
+            &self,
+            id: Uuid,
+            current_hash: &str,
+            new_hash: &str,
+            now: DateTime<Utc>,
+            expires_at: DateTime<Utc>,
+        ) -> Result<Option<LoginSession>> {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(session) = sessions.iter_mut().find(|session| {
+                session.id == id && session.refresh_token_hash == current_hash && session.revoked_at.is_none()
+            }) else {
+                return Ok(None);
+            };
+            session.previous_token_hash = Some(std::mem::replace(&mut session.refresh_token_hash, new_hash.to_string()));
+            session.last_used_at = now;
+            session.expires_at = expires_at;
+            Ok(Some(session.clone()))
+        }
+
+        async fn revoke(&self, id: Uuid, customer_id: Uuid, reason: &str) -> Result<bool> {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(session) = sessions.iter_mut().find(|session| {
+                session.id == id && session.customer_id == customer_id && session.revoked_at.is_none()
+            }) else {
+                return Ok(false);
+            };
+            session.revoked_at = Some(Utc::now());
+            session.revoked_reason = Some(reason.to_string());
+            Ok(true)
+        }
+
+        async fn revoke_all(&self, customer_id: Uuid, reason: &str) -> Result<u64> {
+            let mut revoked = 0;
+            for session in self.sessions.lock().unwrap().iter_mut() {
+                if session.customer_id == customer_id && session.revoked_at.is_none() {
+                    session.revoked_at = Some(Utc::now());
+                    session.revoked_reason = Some(reason.to_string());
+                    revoked += 1;
+                }
+            }
+            Ok(revoked)
+        }
+
+        async fn list_active(&self, customer_id: Uuid, now: DateTime<Utc>) -> Result<Vec<LoginSession>> {
+            Ok(self
+                .sessions
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|session| session.customer_id == customer_id && session.is_active(now))
+                .cloned()
+                .collect())
+        }
+
+        async fn purge(&self, before: DateTime<Utc>) -> Result<u64> {
+            let mut sessions = self.sessions.lock().unwrap();
+            let count = sessions.len();
+            sessions.retain(|session| session.expires_at >= before && session.revoked_at.map_or(true, |at| at >= before));
+            Ok((count - sessions.len()) as u64)
+        }
+    }
+
+    fn service() -> LoginSessionService<MockRepository> {
+        LoginSessionService::new(MockRepository::default(), JwtConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_token() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let issued = service.start(customer_id, &LoginDevice::default(), Utc::now()).await.unwrap();
+        assert!(issued.refresh_token.starts_with(REFRESH_TOKEN_PREFIX));
+
+        let refreshed = service.refresh(&issued.refresh_token, Utc::now()).await.unwrap();
+        assert_eq!(refreshed.session.id, issued.session.id);
+        assert_ne!(refreshed.refresh_token, issued.refresh_token);
+        let session_id = service.refresh_session_id(&refreshed.refresh_token, Utc::now()).await.unwrap();
+        assert_eq!(session_id, Some(issued.session.id));
+        assert_eq!(service.refresh_session_id(&issued.refresh_token, Utc::now()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_reused_token_revokes_session() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let issued = service.start(customer_id, &LoginDevice::default(), Utc::now()).await.unwrap();
+        let refreshed = service.refresh(&issued.refresh_token, Utc::now()).await.unwrap();
+
+        // The old token again: the session ends for both holders
+        assert!(service.refresh(&issued.refresh_token, Utc::now()).await.is_err());
+        assert!(service.refresh(&refreshed.refresh_token, Utc::now()).await.is_err());
+        assert!(service.list(customer_id, Utc::now()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_one_and_all() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let phone = service.start(customer_id, &LoginDevice::default(), Utc::now()).await.unwrap();
+        let laptop = service.start(customer_id, &LoginDevice::default(), Utc::now()).await.unwrap();
+
+        service.revoke(customer_id, phone.session.id, "signed out").await.unwrap();
+        assert!(service.refresh(&phone.refresh_token, Utc::now()).await.is_err());
+        assert!(!service.is_active(phone.session.id, Utc::now()).await.unwrap());
+        assert!(service.is_active(laptop.session.id, Utc::now()).await.unwrap());
+        assert!(service.revoke(Uuid::new_v4(), laptop.session.id, "signed out").await.is_err());
+        assert_eq!(service.list(customer_id, Utc::now()).await.unwrap().len(), 1);
+
+        assert_eq!(service.revoke_all(customer_id, "logout all").await.unwrap(), 1);
+        assert!(service.refresh(&laptop.refresh_token, Utc::now()).await.is_err());
+        assert!(!service.is_active(laptop.session.id, Utc::now()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_access_tokens_of_ended_sessions_are_rejected() {
+        let mut config = crate::Config::default();
+        config.security.jwt.secret = "this_is_a_test_secret_that_is_at_least_32_bytes_long".to_string();
+        let auth = AuthService::new(config);
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let issued = service.start(customer_id, &LoginDevice::default(), Utc::now()).await.unwrap();
+        let token = auth
+            .generate_session_access_token(customer_id, "test@example.com", Vec::new(), issued.session.id)
+            .unwrap();
+        let plain = auth.generate_access_token_with_permissions(customer_id, "test@example.com", Vec::new()).unwrap();
+
+        assert_eq!(service.verify_access_token(&auth, &token, Utc::now()).await.unwrap().sub, customer_id);
+        service.revoke(customer_id, issued.session.id, "signed out").await.unwrap();
+        let rejected = service.verify_access_token(&auth, &token, Utc::now()).await;
+        assert!(matches!(rejected, Err(Error::Unauthorized(_))));
+        // Tokens issued without a session are unaffected
+        assert!(service.verify_access_token(&auth, &plain, Utc::now()).await.is_ok());
+        assert!(matches!(service.verify_access_token(&auth, "not.a.token", Utc::now()).await, Err(Error::Unauthorized(_))));
+    }
+
+    #[test]
+    fn test_expiry_is_capped_at_absolute_lifetime() {
+        let service = service();
+        let login = Utc::now();
+        assert_eq!(service.expiry(login, login), login + Duration::days(14));
+
+        // Refreshing near the end of the 30 days doesn't extend past them
+        let late = login + Duration::days(25);
+        assert_eq!(service.expiry(login, late), login + Duration::days(30));
+    }
+}
@@ -0,0 +1,329 @@
+//! Media Service
+//!
+//! Stores product images in the configured [`MediaStorage`], with a
+//! resized copy per configured image size, and keeps their order, alt
+//! text and variant in `product_images`. Images are uploaded directly or
+//! downloaded from a URL (as importers do).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::MediaConfig;
+use crate::media::{image_dimensions, media_storage, ImageFormat, ImageMagickProcessor, ImageProcessor, MediaStorage};
+use crate::models::{
+    AddProductImageRequest, ImageVariant, ProductImage, ReorderProductImagesRequest, UpdateProductImageRequest,
+};
+use crate::repository::{NewProductImage, ProductImageRepository};
+use crate::{Error, Result};
+
+/// Longest accepted alt text
+const MAX_ALT_TEXT_LENGTH: usize = 255;
+
+/// Media service
+pub struct MediaService<R: ProductImageRepository> {
+    repository: R,
+    storage: Arc<dyn MediaStorage>,
+    processor: Option<Arc<dyn ImageProcessor>>,
+    config: MediaConfig,
+    http: reqwest::Client,
+}
+
+impl<R: ProductImageRepository> MediaService<R> {
+    /// Create the service; images are resized with the command in
+    /// `media.image_processing` when it is enabled
+    pub fn new(repository: R, storage: Arc<dyn MediaStorage>, config: MediaConfig) -> Self {
+        let processor: Option<Arc<dyn ImageProcessor>> = config
+            .image_processing
+            .enabled
+            .then(|| Arc::new(ImageMagickProcessor::new(&config.image_processing.command)) as _);
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .unwrap_or_default();
+        Self {
+            repository,
+            storage,
+            processor,
+            config,
+            http,
+        }
+    }
+
+    /// Create the service with the storage configured in `config`
+    pub fn from_config(repository: R, config: MediaConfig) -> Result<Self> {
+        let storage = media_storage(&config)?;
+        Ok(Self::new(repository, storage, config))
+    }
+
+    /// Resize images with another processor, or not at all
+    pub fn with_processor(mut self, processor: Option<Arc<dyn ImageProcessor>>) -> Self {
+        self.processor = processor;
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn storage(&self) -> &Arc<dyn MediaStorage> {
+        &self.storage
+    }
+
+    /// Largest accepted image in bytes
+    pub fn max_upload_bytes(&self) -> usize {
+        self.config.max_upload_bytes
+    }
+
+    /// A product's images by position
+    pub async fn list_images(&self, product_id: Uuid) -> Result<Vec<ProductImage>> {
+        self.check_product(product_id).await?;
+        self.repository.list(product_id).await
+    }
+
+    /// Store an uploaded image as the product's last image
+    pub async fn upload_image(
+        &self,
+        product_id: Uuid,
+        data: Vec<u8>,
+        alt_text: Option<String>,
+        variant_id: Option<Uuid>,
+    ) -> Result<ProductImage> {
+        check_alt_text(alt_text.as_deref())?;
+        self.check_product(product_id).await?;
+        self.store_image(product_id, data, alt_text, variant_id, None).await
+    }
+
+    /// Download an image into storage as the product's last image. An
+    /// image already downloaded from the same URL is returned as it is.
+    pub async fn import_image(&self, product_id: Uuid, request: AddProductImageRequest) -> Result<ProductImage> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.check_product(product_id).await?;
+        if let Some(image) = self.repository.find_by_source_url(product_id, &request.url).await? {
+            return Ok(image);
+        }
+
+        let data = self.download(&request.url).await?;
+        self.store_image(product_id, data, request.alt_text, request.variant_id, Some(request.url))
+            .await
+    }
+
+    /// Update an image's alt text or variant
+    pub async fn update_image(
+        &self,
+        product_id: Uuid,
+        image_id: Uuid,
+        request: UpdateProductImageRequest,
+    ) -> Result<ProductImage> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.repository
+            .update(product_id, image_id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Image not found"))
+    }
+
+    /// Put a product's images in order
+    pub async fn reorder_images(
+        &self,
+        product_id: Uuid,
+        request: ReorderProductImagesRequest,
+    ) -> Result<Vec<ProductImage>> {
+        let images = self.list_images(product_id).await?;
+        if let Some(id) = request.image_ids.iter().find(|id| !images.iter().any(|image| image.id == **id)) {
+            return Err(Error::validation(format!("Image {} does not belong to this product", id)));
+        }
+        self.repository.reorder(product_id, &request.image_ids).await
+    }
+
+    /// Delete an image and its files
+    pub async fn delete_image(&self, product_id: Uuid, image_id: Uuid) -> Result<()> {
+        let image = self
+            .repository
+            .delete(product_id, image_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Image not found"))?;
+
+        let keys = image
+            .storage_key
+            .iter()
+            .chain(image.variants.iter().map(|variant| &variant.storage_key))
+            .filter(|key| !key.is_empty());
+        for key in keys {
+            if let Err(e) = self.storage.delete(key).await {
+                tracing::warn!("Failed to delete media file {}: {}", key, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn check_product(&self, product_id: Uuid) -> Result<()> {
+        if !self.repository.product_exists(product_id).await? {
+            return Err(Error::not_found("Product not found"));
+        }
+        Ok(())
+    }
+
+    /// Download an image, up to the size limit
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let mut response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| Error::network(format!("Failed to download {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(Error::network(format!("Failed to download {}: {}", url, response.status())));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::network(format!("Failed to download {}: {}", url, e)))?
+        {
+            if data.len() + chunk.len() > self.config.max_upload_bytes {
+                return Err(too_large(self.config.max_upload_bytes));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data)
+    }
+
+    async fn store_image(
+        &self,
+        product_id: Uuid,
+        data: Vec<u8>,
+        alt_text: Option<String>,
+        variant_id: Option<Uuid>,
+        source_url: Option<String>,
+    ) -> Result<ProductImage> {
+        if data.len() > self.config.max_upload_bytes {
+            return Err(too_large(self.config.max_upload_bytes));
+        }
+        let format = ImageFormat::detect(&data)
+            .ok_or_else(|| Error::validation("Images must be JPEG, PNG, GIF or WebP"))?;
+        let dimensions = image_dimensions(&data);
+
+        let id = Uuid::new_v4();
+        let variants = self.store_variants(product_id, id, &data, format).await;
+        let storage_key = image_key(product_id, id, None, format);
+        let file_size = data.len() as i64;
+        if let Err(e) = self.storage.put(&storage_key, data, format.content_type()).await {
+            self.remove_variants(&variants).await;
+            return Err(e);
+        }
+
+        let image = NewProductImage {
+            id,
+            product_id,
+            variant_id,
+            src: self.storage.url(&storage_key),
+            alt_text,
+            width: dimensions.map(|(width, _)| width as i32),
+            height: dimensions.map(|(_, height)| height as i32),
+            storage_key: storage_key.clone(),
+            content_type: format.content_type().to_string(),
+            file_size,
+            variants,
+            source_url,
+        };
+        match self.repository.create(&image).await {
+            Ok(image) => Ok(image),
+            Err(e) => {
+                let _ = self.storage.delete(&storage_key).await;
+                self.remove_variants(&image.variants).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Resize and store an image in each configured size; sizes that fail
+    /// are left out, the original is still usable
+    async fn store_variants(&self, product_id: Uuid, id: Uuid, data: &[u8], format: ImageFormat) -> Vec<ImageVariant> {
+        let Some(processor) = &self.processor else {
+            return Vec::new();
+        };
+
+        let quality = self.config.image_processing.default_quality;
+        let mut variants = Vec::new();
+        for size in &self.config.image_processing.sizes {
+            let resized = match processor.resize(data, format, size, quality).await {
+                Ok(resized) => resized,
+                Err(e) => {
+                    tracing::warn!("Failed to create {} image for product {}: {}", size.name, product_id, e);
+                    continue;
+                }
+            };
+            let dimensions = image_dimensions(&resized);
+            let key = image_key(product_id, id, Some(&size.name), format);
+            if let Err(e) = self.storage.put(&key, resized, format.content_type()).await {
+                tracing::warn!("Failed to store {} image for product {}: {}", size.name, product_id, e);
+                continue;
+            }
+            variants.push(ImageVariant {
+                name: size.name.clone(),
+                src: self.storage.url(&key),
+                width: dimensions.map(|(width, _)| width as i32),
+                height: dimensions.map(|(_, height)| height as i32),
+                storage_key: key,
+            });
+        }
+        variants
+    }
+
+    async fn remove_variants(&self, variants: &[ImageVariant]) {
+        for variant in variants {
+            let _ = self.storage.delete(&variant.storage_key).await;
+        }
+    }
+}
+
+/// Storage key of an image, or of one of its sizes
+fn image_key(product_id: Uuid, image_id: Uuid, size: Option<&str>, format: ImageFormat) -> String {
+    match size {
+        Some(size) => format!("products/{}/{}_{}.{}", product_id, image_id, size, format.extension()),
+        None => format!("products/{}/{}.{}", product_id, image_id, format.extension()),
+    }
+}
+
+fn check_alt_text(alt_text: Option<&str>) -> Result<()> {
+    if alt_text.is_some_and(|text| text.chars().count() > MAX_ALT_TEXT_LENGTH) {
+        return Err(Error::validation(format!(
+            "Alt text can be at most {} characters",
+            MAX_ALT_TEXT_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn too_large(max_bytes: usize) -> Error {
+    Error::validation(format!("Images can be at most {} bytes", max_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_key() {
+        let product_id = Uuid::nil();
+        let image_id = Uuid::from_u128(1);
+        assert_eq!(
+            image_key(product_id, image_id, None, ImageFormat::Jpeg),
+            "products/00000000-0000-0000-0000-000000000000/00000000-0000-0000-0000-000000000001.jpg"
+        );
+        assert_eq!(
+            image_key(product_id, image_id, Some("thumbnail"), ImageFormat::Webp),
+            "products/00000000-0000-0000-0000-000000000000/00000000-0000-0000-0000-000000000001_thumbnail.webp"
+        );
+    }
+
+    #[test]
+    fn test_check_alt_text() {
+        assert!(check_alt_text(None).is_ok());
+        assert!(check_alt_text(Some("Red shirt, front")).is_ok());
+        assert!(check_alt_text(Some(&"a".repeat(256))).is_err());
+    }
+}
@@ -11,12 +11,54 @@ pub mod dunning_service;
 pub mod digital_product_service;
 pub mod bundle_service;
 pub mod checkout_service;
+pub mod invoice_service;
+pub mod formatting_service;
+pub mod geoip_service;
+pub mod order_archive_service;
+pub mod webhook_replay_service;
+pub mod webhook_transform;
+pub mod register_service;
+pub mod price_list_service;
+pub mod address_book_service;
+pub mod addon_service;
+pub mod delivery_service;
+pub mod checkout_field_service;
+pub mod gift_card_service;
+pub mod flash_sale_service;
+pub mod export_service;
+pub mod purchase_limit_service;
+pub mod idempotency_service;
+pub mod login_session_service;
+pub mod two_factor_service;
+pub mod oauth_service;
+pub mod access_log_service;
+pub mod audit_service;
+pub mod soft_delete_service;
+pub mod secret_service;
+pub mod return_service;
+pub mod role_service;
+pub mod hosted_checkout_service;
+pub mod catalog_promotion_service;
+pub mod config_bundle_service;
+pub mod redirect_service;
+pub mod customer_group_service;
+pub mod attribute_service;
+pub mod fraud_service;
+pub mod payment_session_service;
+pub mod payment_capture_service;
+pub mod payout_reconciliation_service;
+pub mod account_service;
+pub mod category_service;
+pub mod collection_service;
+pub mod media_service;
+pub mod print_service;
 
 pub use product_service::ProductService;
 pub use customer_service::CustomerService;
 pub use order_service::OrderService;
 pub use auth_service::AuthService;
 pub use auth_service::ApiKey;
+pub use auth_service::PUBLISHABLE_KEY_PREFIX;
 pub use auth_service::JwtClaims;
 pub use auth_service::AuthenticatedUser;
 pub use auth_service::TokenType;
@@ -30,6 +72,47 @@ pub use statistics_service::{
 pub use dunning_service::{DunningService, DunningHistory, RetryableInvoice, RetryProcessingResult};
 pub use digital_product_service::DigitalProductService;
 pub use bundle_service::BundleService;
+pub use invoice_service::InvoiceService;
+pub use formatting_service::{FormattingService, PriceDisplayRule, SymbolPosition};
+pub use geoip_service::{GeoIpService, GeoLocation, GeoOverride, GeoSource};
+pub use order_archive_service::OrderArchiveService;
+pub use webhook_replay_service::{WebhookReplayService, post_webhook, sign_webhook_body, sign_webhook_payload};
+pub use webhook_transform::{WebhookEnvelope, WebhookTransform, WebhookRequest, RESERVED_WEBHOOK_HEADERS};
+pub use register_service::RegisterService;
+pub use price_list_service::PriceListService;
+pub use address_book_service::AddressBookService;
+pub use addon_service::AddonService;
+pub use delivery_service::DeliveryService;
+pub use checkout_field_service::{CheckoutFieldService, CheckoutFieldSchema};
+pub use gift_card_service::GiftCardService;
+pub use flash_sale_service::FlashSaleService;
+pub use export_service::{ExportEncoder, ExportService};
+pub use purchase_limit_service::{PurchaseLimitService, PurchaseLimiter};
+pub use idempotency_service::{IdempotencyService, IdempotentRequest};
+pub use login_session_service::{IssuedRefreshToken, LoginSessionService};
+pub use two_factor_service::{IssuedTwoFactorChallenge, TwoFactorService};
+pub use oauth_service::OAuthService;
+pub use access_log_service::{AccessLogRetentionJob, AccessLogService};
+pub use audit_service::{AuditRetentionJob, AuditService};
+pub use soft_delete_service::{SoftDeletePurgeJob, SoftDeleteService};
+pub use secret_service::{ReencryptReport, SecretService};
+pub use return_service::{ReturnService, ReturnRefunder, GatewayRefunder};
+pub use role_service::{RoleService, permission_catalog, resource_access};
+pub use hosted_checkout_service::HostedCheckoutService;
+pub use catalog_promotion_service::{diff_catalogs, CatalogPromotionService};
+pub use config_bundle_service::ConfigBundleService;
+pub use redirect_service::RedirectService;
+pub use customer_group_service::CustomerGroupService;
+pub use attribute_service::AttributeService;
+pub use fraud_service::{FraudScreen, RiskScorer};
+pub use payment_session_service::PaymentSessionService;
+pub use payment_capture_service::{AuthorizationExpiryJob, CaptureOnShipment, PaymentCaptureService};
+pub use payout_reconciliation_service::{PayoutReconciliationJob, PayoutReconciliationService};
+pub use account_service::AccountService;
+pub use category_service::CategoryService;
+pub use collection_service::CollectionService;
+pub use media_service::MediaService;
+pub use print_service::PrintService;
 pub use checkout_service::{
     CheckoutService, CheckoutConfig, CheckoutSummary, CheckoutResult,
     InitiateCheckoutRequest, SelectShippingRequest, CompleteCheckoutRequest,
@@ -0,0 +1,810 @@
+//! OAuth / OpenID Connect Login Service
+//!
+//! Social login with Google, Apple and GitHub through the authorization code
+//! flow (with PKCE where the provider supports it). The storefront sends the
+//! browser to `/auth/oauth/:provider/authorize`, the provider redirects back
+//! to the callback, and the browser returns to the storefront with a
+//! one-time login code that it exchanges for tokens or a session.
+//!
+//! A provider account is matched to a customer by the account ID linked
+//! earlier, then by the provider-verified email, and finally creates a new
+//! customer when `create_customers` is on. Emails the provider didn't verify
+//! are never used to find or create an account.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::config::{OAuthConfig, OAuthProviderConfig};
+use crate::models::{
+    hash_oauth_token, ExternalProfile, NewOAuthLogin, OAuthCallbackParams, OAuthIdentity, OAuthLogin,
+    OAuthProvider, OAUTH_LOGIN_CODE_PREFIX,
+};
+use crate::repository::OAuthRepository;
+use crate::{Error, Result};
+
+/// Random characters of a state, PKCE verifier, nonce and login code
+const STATE_LENGTH: usize = 43;
+const VERIFIER_LENGTH: usize = 64;
+const NONCE_LENGTH: usize = 32;
+const LOGIN_CODE_LENGTH: usize = 48;
+
+/// Seconds the storefront has to exchange a login code
+const LOGIN_CODE_TTL_SECS: i64 = 120;
+
+/// Lifetime of the signed client secret sent to Apple
+const APPLE_CLIENT_SECRET_TTL_SECS: i64 = 300;
+
+/// Expired logins are kept this long before being purged
+const PURGE_AFTER_HOURS: i64 = 24;
+
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Where a provider's endpoints are and what it expects
+struct ProviderSpec {
+    authorize_url: &'static str,
+    token_url: &'static str,
+    default_scopes: &'static [&'static str],
+    /// Accepted `iss` of ID tokens; empty for plain OAuth
+    issuers: &'static [&'static str],
+    pkce: bool,
+}
+
+fn spec(provider: OAuthProvider) -> ProviderSpec {
+    match provider {
+        OAuthProvider::Google => ProviderSpec {
+            authorize_url: "https://accounts.google.com/o/oauth2/v2/auth",
+            token_url: "https://oauth2.googleapis.com/token",
+            default_scopes: &["openid", "email", "profile"],
+            issuers: &["https://accounts.google.com", "accounts.google.com"],
+            pkce: true,
+        },
+        // Apple posts the callback as a form and doesn't document PKCE
+        OAuthProvider::Apple => ProviderSpec {
+            authorize_url: "https://appleid.apple.com/auth/authorize",
+            token_url: "https://appleid.apple.com/auth/token",
+            default_scopes: &["name", "email"],
+            issuers: &["https://appleid.apple.com"],
+            pkce: false,
+        },
+        OAuthProvider::Github => ProviderSpec {
+            authorize_url: "https://github.com/login/oauth/authorize",
+            token_url: "https://github.com/login/oauth/access_token",
+            default_scopes: &["read:user", "user:email"],
+            issuers: &[],
+            pkce: true,
+        },
+    }
+}
+
+/// Why a callback didn't produce a login; sent to the storefront as `oauth_error`
+#[derive(Debug)]
+enum LoginFailure {
+    /// The customer declined at the provider
+    Denied,
+    /// No verified email to find or create an account with
+    EmailNotVerified,
+    /// An account has the email, but linking by email is off
+    AccountExists,
+    /// No account, and creating customers is off
+    NoAccount,
+    Provider(Error),
+    Server(Error),
+}
+
+impl LoginFailure {
+    fn code(&self) -> &'static str {
+        match self {
+            LoginFailure::Denied => "access_denied",
+            LoginFailure::EmailNotVerified => "email_not_verified",
+            LoginFailure::AccountExists => "account_exists",
+            LoginFailure::NoAccount => "account_not_found",
+            LoginFailure::Provider(_) => "provider_error",
+            LoginFailure::Server(_) => "server_error",
+        }
+    }
+}
+
+impl From<Error> for LoginFailure {
+    fn from(e: Error) -> Self {
+        LoginFailure::Server(e)
+    }
+}
+
+/// OAuth login service
+pub struct OAuthService<R: OAuthRepository> {
+    repository: R,
+    config: OAuthConfig,
+    http: reqwest::Client,
+}
+
+impl<R: OAuthRepository> OAuthService<R> {
+    pub fn new(repository: R, config: OAuthConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECS))
+            .user_agent(concat!("rcommerce/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self { repository, config, http }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Providers customers can log in with
+    pub fn providers(&self) -> Vec<OAuthProvider> {
+        OAuthProvider::ALL.into_iter().filter(|p| self.config.provider(*p).is_some()).collect()
+    }
+
+    fn provider_config(&self, provider: OAuthProvider) -> Result<&OAuthProviderConfig> {
+        self.config
+            .provider(provider)
+            .ok_or_else(|| Error::not_found(format!("Login with {} is not enabled", provider)))
+    }
+
+    /// Start a login: returns the provider URL to send the browser to.
+    /// `client_state` is handed back to `return_to` with the result.
+    pub async fn authorize(
+        &self,
+        provider: OAuthProvider,
+        return_to: &str,
+        client_state: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        let config = self.provider_config(provider)?;
+        if !return_to_allowed(&self.config.allowed_return_urls, return_to) {
+            return Err(Error::validation("return_to is not an allowed return URL"));
+        }
+        if client_state.is_some_and(|state| state.len() > 255) {
+            return Err(Error::validation("state must be at most 255 characters"));
+        }
+
+        let state = random_token(STATE_LENGTH);
+        let login = NewOAuthLogin {
+            provider,
+            state_hash: hash_oauth_token(&state),
+            code_verifier: random_token(VERIFIER_LENGTH),
+            nonce: random_token(NONCE_LENGTH),
+            return_to: return_to.to_string(),
+            client_state: client_state.map(str::to_string),
+            expires_at: now + Duration::seconds(self.config.state_ttl_secs as i64),
+        };
+        self.repository.create_login(&login).await?;
+
+        authorization_url(provider, config, &state, &pkce_challenge(&login.code_verifier), &login.nonce)
+    }
+
+    /// Handle the provider's redirect back: returns the storefront URL to
+    /// send the browser to, with either `oauth_code` or `oauth_error`
+    pub async fn callback(&self, provider: OAuthProvider, params: &OAuthCallbackParams, now: DateTime<Utc>) -> Result<String> {
+        let state = params.state.as_deref().ok_or_else(|| Error::validation("Missing state"))?;
+        let login = self
+            .repository
+            .take_login(&hash_oauth_token(state), now)
+            .await?
+            .filter(|login| login.provider == provider.as_str())
+            .ok_or_else(|| Error::validation("This login expired or was already used; please start again"))?;
+
+        let outcome = match self.resolve(provider, &login, params, now).await {
+            Ok((customer_id, created)) => self.issue_login_code(&login, customer_id, created, now).await,
+            Err(failure) => Err(failure),
+        };
+        let result = match outcome {
+            Ok(code) => ("oauth_code", code),
+            Err(failure) => {
+                match &failure {
+                    LoginFailure::Provider(e) => tracing::warn!("Login with {} failed at the provider: {}", provider, e),
+                    LoginFailure::Server(e) => tracing::error!("Login with {} failed: {}", provider, e),
+                    other => tracing::info!("Login with {} refused: {}", provider, other.code()),
+                }
+                ("oauth_error", failure.code().to_string())
+            }
+        };
+        Ok(return_url(&login.return_to, result, login.client_state.as_deref()))
+    }
+
+    async fn issue_login_code(
+        &self,
+        login: &OAuthLogin,
+        customer_id: Uuid,
+        created: bool,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<String, LoginFailure> {
+        let code = format!("{}{}", OAUTH_LOGIN_CODE_PREFIX, random_token(LOGIN_CODE_LENGTH));
+        let expires_at = now + Duration::seconds(LOGIN_CODE_TTL_SECS);
+        self.repository
+            .complete_login(login.id, customer_id, created, &hash_oauth_token(&code), expires_at)
+            .await?;
+        tracing::info!("Customer {} logged in with {}{}", customer_id, login.provider, if created { " (new account)" } else { "" });
+        Ok(code)
+    }
+
+    /// The customer of a callback, and whether they were just created
+    async fn resolve(
+        &self,
+        provider: OAuthProvider,
+        login: &OAuthLogin,
+        params: &OAuthCallbackParams,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<(Uuid, bool), LoginFailure> {
+        if params.error.is_some() {
+            return Err(LoginFailure::Denied);
+        }
+        let code = params
+            .code
+            .as_deref()
+            .ok_or_else(|| LoginFailure::Provider(Error::validation("Callback without an authorization code")))?;
+        let profile = self
+            .fetch_profile(provider, code, login, params.user.as_deref(), now)
+            .await
+            .map_err(LoginFailure::Provider)?;
+        self.resolve_customer(&profile).await
+    }
+
+    async fn resolve_customer(&self, profile: &ExternalProfile) -> std::result::Result<(Uuid, bool), LoginFailure> {
+        if let Some(identity) = self.repository.find_identity(profile.provider, &profile.subject).await? {
+            self.repository.touch_identity(identity.id, profile.verified_email()).await?;
+            return Ok((identity.customer_id, false));
+        }
+
+        let email = profile.verified_email().ok_or(LoginFailure::EmailNotVerified)?;
+        if let Some(customer_id) = self.repository.find_customer_by_email(email).await? {
+            if !self.config.link_by_email {
+                return Err(LoginFailure::AccountExists);
+            }
+            self.repository.link_identity(customer_id, profile).await?;
+            tracing::info!("Linked {} account to customer {} by verified email", profile.provider, customer_id);
+            return Ok((customer_id, false));
+        }
+
+        if !self.config.create_customers {
+            return Err(LoginFailure::NoAccount);
+        }
+        let (first_name, last_name) = profile_names(profile, email);
+        let customer_id = self.repository.create_customer(profile, &first_name, &last_name).await?;
+        Ok((customer_id, true))
+    }
+
+    /// Use up a login code from the callback; returns its customer
+    pub async fn exchange_login_code(&self, code: &str, now: DateTime<Utc>) -> Result<Uuid> {
+        self.repository
+            .consume_login_code(&hash_oauth_token(code.trim()), now)
+            .await?
+            .and_then(|login| login.customer_id)
+            .ok_or_else(|| Error::unauthorized("Invalid or expired login code"))
+    }
+
+    /// Provider accounts linked to a customer
+    pub async fn identities(&self, customer_id: Uuid) -> Result<Vec<OAuthIdentity>> {
+        self.repository.list_identities(customer_id).await
+    }
+
+    /// Unlink a provider account, unless it is the customer's only way to log in
+    pub async fn unlink(&self, customer_id: Uuid, identity_id: Uuid) -> Result<()> {
+        let identities = self.repository.list_identities(customer_id).await?;
+        if !identities.iter().any(|identity| identity.id == identity_id) {
+            return Err(Error::not_found("Linked account not found"));
+        }
+        if identities.len() == 1 && !self.repository.has_password(customer_id).await? {
+            return Err(Error::validation("Set a password before unlinking your only login provider"));
+        }
+        self.repository.unlink_identity(customer_id, identity_id).await?;
+        Ok(())
+    }
+
+    /// Delete logins expired for a day; returns how many
+    pub async fn purge(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repository.purge_logins(now - Duration::hours(PURGE_AFTER_HOURS)).await
+    }
+
+    async fn fetch_profile(
+        &self,
+        provider: OAuthProvider,
+        code: &str,
+        login: &OAuthLogin,
+        apple_user: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<ExternalProfile> {
+        let config = self.provider_config(provider)?;
+        let tokens = self.exchange_code(provider, config, code, &login.code_verifier, now).await?;
+        match provider {
+            OAuthProvider::Github => self.github_profile(&tokens.access_token).await,
+            OAuthProvider::Google | OAuthProvider::Apple => {
+                let id_token = tokens
+                    .id_token
+                    .ok_or_else(|| Error::Other(format!("{} returned no ID token", provider)))?;
+                let mut profile = id_token_profile(provider, &config.client_id, &id_token, &login.nonce)?;
+                // Apple only sends the name, outside the ID token, on the first login
+                if let Some(name) = apple_user.and_then(|user| serde_json::from_str::<AppleUser>(user).ok()).and_then(|user| user.name) {
+                    profile.first_name = name.first_name.or(profile.first_name);
+                    profile.last_name = name.last_name.or(profile.last_name);
+                }
+                Ok(profile)
+            }
+        }
+    }
+
+    async fn exchange_code(
+        &self,
+        provider: OAuthProvider,
+        config: &OAuthProviderConfig,
+        code: &str,
+        verifier: &str,
+        now: DateTime<Utc>,
+    ) -> Result<TokenResponse> {
+        let spec = spec(provider);
+        let client_secret = match provider {
+            OAuthProvider::Apple => apple_client_secret(config, now)?,
+            _ => config.client_secret.clone(),
+        };
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+        ];
+        if spec.pkce {
+            form.push(("code_verifier", verifier));
+        }
+
+        let response = self
+            .http
+            .post(spec.token_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to reach {}: {}", provider, e)))?;
+        let status = response.status();
+        let body: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::Other(format!("Invalid token response from {} ({}): {}", provider, status, e)))?;
+        // GitHub reports errors with a 200
+        if let Some(error) = body.error {
+            return Err(Error::Other(format!(
+                "{} rejected the authorization code: {} {}",
+                provider,
+                error,
+                body.error_description.unwrap_or_default()
+            )));
+        }
+        if !status.is_success() || body.access_token.is_empty() {
+            return Err(Error::Other(format!("{} rejected the authorization code ({})", provider, status)));
+        }
+        Ok(body)
+    }
+
+    async fn github_profile(&self, access_token: &str) -> Result<ExternalProfile> {
+        let user: GithubUser = self.github_get(access_token, "/user").await?;
+        // Needs the user:email scope; without it only the public email is known, unverified
+        let primary = match self.github_get::<Vec<GithubEmail>>(access_token, "/user/emails").await {
+            Ok(emails) => emails.into_iter().find(|email| email.primary && email.verified),
+            Err(e) => {
+                tracing::debug!("GitHub emails unavailable: {}", e);
+                None
+            }
+        };
+        let (first_name, last_name) = user.name.as_deref().map(split_name).unwrap_or((None, None));
+        Ok(ExternalProfile {
+            provider: OAuthProvider::Github,
+            subject: user.id.to_string(),
+            email_verified: primary.is_some(),
+            email: primary.map(|email| email.email).or(user.email),
+            first_name: first_name.or(Some(user.login)),
+            last_name,
+        })
+    }
+
+    async fn github_get<T: serde::de::DeserializeOwned>(&self, access_token: &str, path: &str) -> Result<T> {
+        self.http
+            .get(format!("{}{}", GITHUB_API, path))
+            .bearer_auth(access_token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::Other(format!("Failed to get GitHub {}: {}", path, e)))?
+            .json()
+            .await
+            .map_err(|e| Error::Other(format!("Invalid GitHub {} response: {}", path, e)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    access_token: String,
+    id_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    /// Google sends a boolean, Apple sometimes the string "true"
+    email_verified: Option<serde_json::Value>,
+    given_name: Option<String>,
+    family_name: Option<String>,
+    nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppleUser {
+    name: Option<AppleName>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppleName {
+    first_name: Option<String>,
+    last_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct AppleClientSecretClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+    exp: i64,
+    aud: &'a str,
+    sub: &'a str,
+}
+
+/// Read the profile from an ID token received straight from the token
+/// endpoint. Its signature is not checked: the TLS connection to the
+/// provider already authenticates it (OpenID Connect Core 3.1.3.7); issuer,
+/// audience, expiry and nonce are.
+fn id_token_profile(provider: OAuthProvider, client_id: &str, id_token: &str, nonce: &str) -> Result<ExternalProfile> {
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.insecure_disable_signature_validation();
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(spec(provider).issuers);
+    let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &DecodingKey::from_secret(&[]), &validation)
+        .map_err(|e| Error::unauthorized(format!("Invalid ID token from {}: {}", provider, e)))?
+        .claims;
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(Error::unauthorized(format!("ID token from {} has the wrong nonce", provider)));
+    }
+
+    let email_verified = match claims.email_verified {
+        Some(serde_json::Value::Bool(verified)) => verified,
+        Some(serde_json::Value::String(verified)) => verified == "true",
+        _ => false,
+    };
+    Ok(ExternalProfile {
+        provider,
+        subject: claims.sub,
+        email: claims.email,
+        email_verified,
+        first_name: claims.given_name,
+        last_name: claims.family_name,
+    })
+}
+
+/// Apple's client secret: a short-lived ES256 JWT signed with the team's key
+fn apple_client_secret(config: &OAuthProviderConfig, now: DateTime<Utc>) -> Result<String> {
+    let (Some(team_id), Some(key_id), Some(key_path)) = (&config.team_id, &config.key_id, &config.private_key_path) else {
+        return Err(Error::Config("security.oauth.apple needs team_id, key_id and private_key_path".to_string()));
+    };
+    let pem = std::fs::read(key_path)
+        .map_err(|e| Error::Config(format!("Failed to read Apple key {}: {}", key_path.display(), e)))?;
+    let key = EncodingKey::from_ec_pem(&pem).map_err(|e| Error::Config(format!("Invalid Apple key: {}", e)))?;
+
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(key_id.clone());
+    let claims = AppleClientSecretClaims {
+        iss: team_id,
+        iat: now.timestamp(),
+        exp: now.timestamp() + APPLE_CLIENT_SECRET_TTL_SECS,
+        aud: "https://appleid.apple.com",
+        sub: &config.client_id,
+    };
+    jsonwebtoken::encode(&header, &claims, &key).map_err(|e| Error::Other(format!("Failed to sign Apple client secret: {}", e)))
+}
+
+fn authorization_url(
+    provider: OAuthProvider,
+    config: &OAuthProviderConfig,
+    state: &str,
+    code_challenge: &str,
+    nonce: &str,
+) -> Result<String> {
+    let spec = spec(provider);
+    let scope = if config.scopes.is_empty() {
+        spec.default_scopes.join(" ")
+    } else {
+        config.scopes.join(" ")
+    };
+    let mut url = url::Url::parse(spec.authorize_url).map_err(|e| Error::Other(format!("Invalid authorize URL: {}", e)))?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &config.client_id)
+            .append_pair("redirect_uri", &config.redirect_uri)
+            .append_pair("scope", &scope)
+            .append_pair("state", state);
+        if spec.pkce {
+            query.append_pair("code_challenge", code_challenge).append_pair("code_challenge_method", "S256");
+        }
+        if !spec.issuers.is_empty() {
+            query.append_pair("nonce", nonce);
+        }
+        if provider == OAuthProvider::Apple {
+            query.append_pair("response_mode", "form_post");
+        }
+    }
+    Ok(url.into())
+}
+
+/// Whether `return_to` is on the origin of an allowed URL and under its path
+fn return_to_allowed(allowed: &[String], return_to: &str) -> bool {
+    let Ok(target) = url::Url::parse(return_to) else {
+        return false;
+    };
+    allowed.iter().filter_map(|allowed| url::Url::parse(allowed).ok()).any(|allowed| {
+        allowed.origin() == target.origin()
+            && target.path().starts_with(allowed.path())
+            && target.username().is_empty()
+            && target.password().is_none()
+    })
+}
+
+/// `return_to` with the result and the storefront's state added to the query
+fn return_url(return_to: &str, (key, value): (&str, String), client_state: Option<&str>) -> String {
+    let Ok(mut url) = url::Url::parse(return_to) else {
+        return return_to.to_string();
+    };
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair(key, &value);
+        if let Some(state) = client_state {
+            query.append_pair("state", state);
+        }
+    }
+    url.into()
+}
+
+/// S256 PKCE challenge of a verifier (RFC 7636)
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn random_token(length: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect()
+}
+
+/// First and last name of a new customer, falling back to the email's local part
+fn profile_names(profile: &ExternalProfile, email: &str) -> (String, String) {
+    let first_name = profile
+        .first_name
+        .clone()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_string());
+    (first_name, profile.last_name.clone().unwrap_or_default())
+}
+
+/// "Ada Lovelace" -> first and last name
+fn split_name(name: &str) -> (Option<String>, Option<String>) {
+    let mut parts = name.trim().splitn(2, ' ');
+    let first = parts.next().filter(|s| !s.is_empty()).map(str::to_string);
+    let last = parts.next().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    (first, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    fn provider_config() -> OAuthProviderConfig {
+        OAuthProviderConfig {
+            client_id: "client-123".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_uri: "https://api.example.com/api/v1/auth/oauth/google/callback".to_string(),
+            scopes: Vec::new(),
+            team_id: None,
+            key_id: None,
+            private_key_path: None,
+        }
+    }
+
+    fn id_token(claims: serde_json::Value) -> String {
+        jsonwebtoken::encode(&Header::default(), &claims, &EncodingKey::from_secret(b"unused")).unwrap()
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mJ92IGqxEqYrNXCHONqk8BQ8Qe3kAcR"),
+            "ru59ybonFRkPnEZ4RI5NsIOOyJ4YTaSR1nJs7__fjbc"
+        );
+    }
+
+    #[test]
+    fn test_return_to_allowed() {
+        let allowed = vec!["https://shop.example.com/account".to_string()];
+        assert!(return_to_allowed(&allowed, "https://shop.example.com/account/login?next=/cart"));
+        assert!(!return_to_allowed(&allowed, "https://shop.example.com/admin"));
+        assert!(!return_to_allowed(&allowed, "http://shop.example.com/account"));
+        assert!(!return_to_allowed(&allowed, "https://shop.example.com.evil.test/account"));
+        assert!(!return_to_allowed(&allowed, "https://user@shop.example.com/account"));
+        assert!(!return_to_allowed(&allowed, "/account"));
+    }
+
+    #[test]
+    fn test_authorization_url() {
+        let url = authorization_url(OAuthProvider::Google, &provider_config(), "st", "ch", "nn").unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(url.host_str(), Some("accounts.google.com"));
+        assert_eq!(query["scope"], "openid email profile");
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["nonce"], "nn");
+
+        let url = authorization_url(OAuthProvider::Github, &provider_config(), "st", "ch", "nn").unwrap();
+        assert!(!url.contains("nonce=") && url.contains("code_challenge=ch"));
+    }
+
+    #[test]
+    fn test_id_token_profile() {
+        let exp = Utc::now().timestamp() + 600;
+        let token = id_token(serde_json::json!({
+            "iss": "https://appleid.apple.com", "aud": "client-123", "exp": exp,
+            "sub": "001234.abcd", "email": "ada@example.com", "email_verified": "true", "nonce": "n1",
+        }));
+        let profile = id_token_profile(OAuthProvider::Apple, "client-123", &token, "n1").unwrap();
+        assert_eq!(profile.subject, "001234.abcd");
+        assert_eq!(profile.verified_email(), Some("ada@example.com"));
+
+        // Another client's token, a replayed nonce, or another issuer is refused
+        assert!(id_token_profile(OAuthProvider::Apple, "client-999", &token, "n1").is_err());
+        assert!(id_token_profile(OAuthProvider::Apple, "client-123", &token, "n2").is_err());
+        assert!(id_token_profile(OAuthProvider::Google, "client-123", &token, "n1").is_err());
+    }
+
+    #[test]
+    fn test_split_name() {
+        assert_eq!(split_name("Ada King Lovelace"), (Some("Ada".to_string()), Some("King Lovelace".to_string())));
+        assert_eq!(split_name("ada"), (Some("ada".to_string()), None));
+    }
+
+    #[derive(Default)]
+    struct MockRepository {
+        identities: Mutex<Vec<OAuthIdentity>>,
+        customers: Mutex<Vec<(Uuid, String)>>,
+    }
+
+    #[async_trait]
+    impl OAuthRepository for MockRepository {
+        async fn find_identity(&self, provider: OAuthProvider, subject: &str) -> Result<Option<OAuthIdentity>> {
+            Ok(self.identities.lock().unwrap().iter().find(|i| i.provider == provider.as_str() && i.subject == subject).cloned())
+        }
+
+        async fn list_identities(&self, customer_id: Uuid) -> Result<Vec<OAuthIdentity>> {
+            Ok(self.identities.lock().unwrap().iter().filter(|i| i.customer_id == customer_id).cloned().collect())
+        }
+
+        async fn touch_identity(&self, _id: Uuid, _email: Option<&str>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn link_identity(&self, customer_id: Uuid, profile: &ExternalProfile) -> Result<OAuthIdentity> {
+            let identity = OAuthIdentity {
+                id: Uuid::new_v4(),
+                customer_id,
+                provider: profile.provider.as_str().to_string(),
+                subject: profile.subject.clone(),
+                email: profile.email.clone(),
+                created_at: Utc::now(),
+                last_login_at: Utc::now(),
+            };
+            self.identities.lock().unwrap().push(identity.clone());
+            Ok(identity)
+        }
+
+        async fn create_customer(&self, profile: &ExternalProfile, _first_name: &str, _last_name: &str) -> Result<Uuid> {
+            let id = Uuid::new_v4();
+            self.customers.lock().unwrap().push((id, profile.email.clone().unwrap()));
+            self.link_identity(id, profile).await?;
+            Ok(id)
+        }
+
+        async fn unlink_identity(&self, customer_id: Uuid, id: Uuid) -> Result<bool> {
+            let mut identities = self.identities.lock().unwrap();
+            let before = identities.len();
+            identities.retain(|i| !(i.id == id && i.customer_id == customer_id));
+            Ok(identities.len() < before)
+        }
+
+        async fn find_customer_by_email(&self, email: &str) -> Result<Option<Uuid>> {
+            Ok(self.customers.lock().unwrap().iter().find(|(_, e)| e.eq_ignore_ascii_case(email)).map(|(id, _)| *id))
+        }
+
+        async fn has_password(&self, _customer_id: Uuid) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn create_login(&self, _login: &NewOAuthLogin) -> Result<OAuthLogin> {
+            unimplemented!()
+        }
+
+        async fn take_login(&self, _state_hash: &str, _now: DateTime<Utc>) -> Result<Option<OAuthLogin>> {
+            Ok(None)
+        }
+
+        async fn complete_login(&self, _id: Uuid, _customer_id: Uuid, _created: bool, _hash: &str, _expires_at: DateTime<Utc>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn consume_login_code(&self, _hash: &str, _now: DateTime<Utc>) -> Result<Option<OAuthLogin>> {
+            Ok(None)
+        }
+
+        async fn purge_logins(&self, _before: DateTime<Utc>) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn profile(subject: &str, email: &str, verified: bool) -> ExternalProfile {
+        ExternalProfile {
+            provider: OAuthProvider::Google,
+            subject: subject.to_string(),
+            email: Some(email.to_string()),
+            email_verified: verified,
+            first_name: None,
+            last_name: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_customer() {
+        let existing = Uuid::new_v4();
+        let repository = MockRepository::default();
+        repository.customers.lock().unwrap().push((existing, "ada@example.com".to_string()));
+        let service = OAuthService::new(repository, OAuthConfig::default());
+
+        // Linked to the existing customer by verified email, then found by account ID
+        let (id, created) = service.resolve_customer(&profile("g-1", "ADA@example.com", true)).await.unwrap();
+        assert_eq!((id, created), (existing, false));
+        let (id, _) = service.resolve_customer(&profile("g-1", "other@example.com", false)).await.unwrap();
+        assert_eq!(id, existing);
+
+        // An unverified email neither links nor creates
+        let failure = service.resolve_customer(&profile("g-2", "ada@example.com", false)).await.unwrap_err();
+        assert_eq!(failure.code(), "email_not_verified");
+
+        let (id, created) = service.resolve_customer(&profile("g-3", "new@example.com", true)).await.unwrap();
+        assert!(created && id != existing);
+
+        // The only login of a passwordless account can't be unlinked
+        let identity = service.identities(id).await.unwrap().remove(0);
+        assert!(service.unlink(id, identity.id).await.is_err());
+    }
+}
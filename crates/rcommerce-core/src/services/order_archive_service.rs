@@ -0,0 +1,206 @@
+//! Order Archive Service
+//!
+//! Moves finished orders older than `archive_after_days` into the archive in
+//! batches, optionally exporting each archived order as a JSON line for cold
+//! storage. Runs periodically from the API server when enabled, and on demand
+//! from the admin API and `rcommerce order archive`.
+
+use chrono::{DateTime, Duration, Utc};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::OrderArchiveConfig;
+use crate::models::{ArchivedOrder, OrderArchiveReport};
+use crate::repository::OrderArchiveRepository;
+use crate::{Error, Result};
+
+/// Order archival service
+pub struct OrderArchiveService<R: OrderArchiveRepository> {
+    repository: R,
+    config: OrderArchiveConfig,
+}
+
+impl<R: OrderArchiveRepository> OrderArchiveService<R> {
+    pub fn new(repository: R, config: OrderArchiveConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn config(&self) -> &OrderArchiveConfig {
+        &self.config
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Orders finished before this are archived
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(self.config.archive_after_days as i64)
+    }
+
+    /// Archive all eligible orders, or only count them on a dry run
+    pub async fn run(&self, dry_run: bool) -> Result<OrderArchiveReport> {
+        let started_at = Utc::now();
+        let cutoff = self.cutoff(started_at);
+        let mut report = OrderArchiveReport {
+            cutoff: Some(cutoff),
+            started_at: Some(started_at),
+            ..Default::default()
+        };
+
+        if dry_run {
+            report.eligible = self.repository.count_archivable(cutoff, &self.config.statuses).await? as u64;
+            report.finished_at = Some(Utc::now());
+            return Ok(report);
+        }
+
+        let export_path = self
+            .config
+            .export_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("orders-archive-{}.jsonl", started_at.format("%Y%m%dT%H%M%SZ"))));
+
+        loop {
+            let batch = self
+                .repository
+                .archive_batch(cutoff, &self.config.statuses, self.config.batch_size as i64)
+                .await?;
+            if batch.is_empty() {
+                break;
+            }
+            report.archived += batch.len() as u64;
+
+            if let Some(ref path) = export_path {
+                export(path, &batch).await.map_err(|e| {
+                    Error::Other(format!(
+                        "Archived {} orders but failed to export them to {} (they remain in orders_archive): {}",
+                        report.archived,
+                        path.display(),
+                        e
+                    ))
+                })?;
+                report.export_file = Some(path.display().to_string());
+            }
+
+            if batch.len() < self.config.batch_size as usize {
+                break;
+            }
+        }
+
+        report.finished_at = Some(Utc::now());
+        Ok(report)
+    }
+}
+
+/// Append archived orders to a JSON Lines file
+async fn export(path: &std::path::Path, orders: &[ArchivedOrder]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    let mut lines = Vec::new();
+    for order in orders {
+        serde_json::to_writer(&mut lines, order)?;
+        lines.push(b'\n');
+    }
+
+    let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+    file.write_all(&lines).await?;
+    file.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, Order, OrderArchiveStats, OrderItem, OrderStatus, PaymentReceipt};
+    use async_trait::async_trait;
+    use rust_decimal::Decimal;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// In-memory repository holding a number of archivable orders
+    struct MockRepository {
+        remaining: Mutex<usize>,
+    }
+
+    fn archived_order() -> ArchivedOrder {
+        ArchivedOrder {
+            id: Uuid::new_v4(),
+            order_number: "1001".to_string(),
+            customer_id: None,
+            email: "customer@example.com".to_string(),
+            status: OrderStatus::Completed,
+            currency: Currency::USD,
+            total: Decimal::new(4999, 2),
+            created_at: Utc::now(),
+            completed_at: None,
+            archived_at: Utc::now(),
+            data: serde_json::json!({}),
+        }
+    }
+
+    #[async_trait]
+    impl OrderArchiveRepository for MockRepository {
+        async fn archive_batch(&self, _cutoff: DateTime<Utc>, _statuses: &[String], limit: i64) -> Result<Vec<ArchivedOrder>> {
+            let mut remaining = self.remaining.lock().unwrap();
+            let count = (*remaining).min(limit as usize);
+            *remaining -= count;
+            Ok((0..count).map(|_| archived_order()).collect())
+        }
+
+        async fn count_archivable(&self, _cutoff: DateTime<Utc>, _statuses: &[String]) -> Result<i64> {
+            Ok(*self.remaining.lock().unwrap() as i64)
+        }
+
+        async fn find(&self, _id: Uuid) -> Result<Option<ArchivedOrder>> {
+            Ok(None)
+        }
+
+        async fn find_order(&self, _id: Uuid) -> Result<Option<Order>> {
+            Ok(None)
+        }
+
+        async fn find_items(&self, _order_id: Uuid) -> Result<Vec<OrderItem>> {
+            Ok(Vec::new())
+        }
+
+        async fn find_receipts(&self, _order_id: Uuid) -> Result<Vec<PaymentReceipt>> {
+            Ok(Vec::new())
+        }
+
+        async fn stats(&self) -> Result<OrderArchiveStats> {
+            Ok(OrderArchiveStats { archived_orders: 0, oldest_order_at: None, last_archived_at: None })
+        }
+    }
+
+    fn service(remaining: usize, config: OrderArchiveConfig) -> OrderArchiveService<MockRepository> {
+        OrderArchiveService::new(MockRepository { remaining: Mutex::new(remaining) }, config)
+    }
+
+    #[tokio::test]
+    async fn test_run_archives_in_batches_and_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = OrderArchiveConfig {
+            batch_size: 2,
+            export_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let archiver = service(5, config);
+
+        let report = archiver.run(false).await.unwrap();
+        assert_eq!(report.archived, 5);
+
+        let export = std::fs::read_to_string(report.export_file.unwrap()).unwrap();
+        assert_eq!(export.lines().count(), 5);
+        assert_eq!(*archiver.repository().remaining.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_only_counts() {
+        let archiver = service(3, OrderArchiveConfig::default());
+
+        let report = archiver.run(true).await.unwrap();
+        assert_eq!(report.eligible, 3);
+        assert_eq!(report.archived, 0);
+        assert_eq!(*archiver.repository().remaining.lock().unwrap(), 3);
+    }
+}
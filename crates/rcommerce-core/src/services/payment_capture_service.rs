@@ -0,0 +1,334 @@
+//! Payment Capture Service
+//!
+//! Captures payments authorized with `payment.capture.method = "manual"`:
+//! staff capture them in part or in full, and with capture-on-fulfillment
+//! (`payment.capture.on_fulfillment`, overridable per order) each shipment
+//! captures the price of what it shipped, the last one releasing the rest.
+//! Authorizations not captured before they lapse are voided by
+//! `AuthorizationExpiryJob`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::config::PaymentCaptureConfig;
+use crate::events::{BusEvent, DomainEvent, EventHandler};
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::models::{
+    CapturePaymentRequest, CaptureSource, NewPaymentCapture, PaymentAuthorization, PaymentCapture,
+};
+use crate::payment::agnostic::{AgnosticPaymentGateway, PaymentService};
+use crate::repository::PaymentCaptureRepository;
+use crate::{Error, Result};
+
+/// Expired authorizations voided per run
+const EXPIRY_BATCH: i64 = 100;
+
+/// Payment capture service
+pub struct PaymentCaptureService<R: PaymentCaptureRepository> {
+    repository: R,
+    payments: Arc<PaymentService>,
+    config: PaymentCaptureConfig,
+}
+
+impl<R: PaymentCaptureRepository> PaymentCaptureService<R> {
+    pub fn new(repository: R, payments: Arc<PaymentService>, config: PaymentCaptureConfig) -> Self {
+        Self { repository, payments, config }
+    }
+
+    pub fn config(&self) -> &PaymentCaptureConfig {
+        &self.config
+    }
+
+    pub async fn authorization(&self, payment_id: Uuid) -> Result<PaymentAuthorization> {
+        self.repository
+            .find_authorization(payment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Authorized payment not found"))
+    }
+
+    pub async fn captures(&self, payment_id: Uuid) -> Result<Vec<PaymentCapture>> {
+        self.repository.captures(payment_id).await
+    }
+
+    /// Capture (part of) an authorized payment
+    pub async fn capture(
+        &self,
+        payment_id: Uuid,
+        request: CapturePaymentRequest,
+        captured_by: Option<Uuid>,
+    ) -> Result<PaymentCapture> {
+        let authorization = self.authorization(payment_id).await?;
+        let amount = capture_amount(&authorization, request.amount, Utc::now())?;
+        let final_capture = request.final_capture || amount == authorization.remaining();
+        self.capture_authorization(&authorization, amount, final_capture, CaptureSource::Manual, None, captured_by)
+            .await
+    }
+
+    /// Release what is left of an authorization
+    pub async fn void(&self, payment_id: Uuid) -> Result<PaymentAuthorization> {
+        let authorization = self.authorization(payment_id).await?;
+        if !authorization.is_open() {
+            return Err(Error::validation("The payment is no longer authorized"));
+        }
+        let (gateway, gateway_payment_id) = self.gateway(&authorization)?;
+        gateway.void_payment(gateway_payment_id).await?;
+        self.repository
+            .record_void(payment_id)
+            .await?
+            .ok_or_else(|| Error::validation("The payment is no longer authorized"))
+    }
+
+    /// Capture what a shipment shipped, if its order captures on fulfillment.
+    /// The order's last shipment makes the final capture.
+    pub async fn capture_shipment(&self, shipment_id: Uuid) -> Result<Option<PaymentCapture>> {
+        let shipment = match self.repository.shipment_capture(shipment_id).await? {
+            Some(shipment) => shipment,
+            None => return Ok(None),
+        };
+        if shipment.captured || !shipment.capture_on_fulfillment.unwrap_or(self.config.on_fulfillment) {
+            return Ok(None);
+        }
+        let authorization = match self.repository.order_authorizations(shipment.order_id).await?.into_iter().next() {
+            Some(authorization) => authorization,
+            None => return Ok(None),
+        };
+        let fully_shipped = shipment.unshipped_units <= 0;
+        let amount = shipment_amount(shipment.value, authorization.remaining(), fully_shipped);
+        if amount <= Decimal::ZERO {
+            if fully_shipped {
+                self.void(authorization.id).await?;
+            }
+            return Ok(None);
+        }
+        let capture = self
+            .capture_authorization(
+                &authorization,
+                amount,
+                fully_shipped,
+                CaptureSource::Fulfillment,
+                Some(shipment_id),
+                None,
+            )
+            .await?;
+        Ok(Some(capture))
+    }
+
+    /// Void lapsed authorizations. They are recorded as voided even when the
+    /// gateway already let them go.
+    pub async fn void_expired(&self) -> Result<usize> {
+        let expired = self.repository.expired_authorizations(Utc::now(), EXPIRY_BATCH).await?;
+        let mut voided = 0;
+        for authorization in expired {
+            let released = match self.gateway(&authorization) {
+                Ok((gateway, gateway_payment_id)) => gateway.void_payment(gateway_payment_id).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = released {
+                tracing::warn!("Failed to void expired authorization {}: {}", authorization.id, e);
+            }
+            if self.repository.record_void(authorization.id).await?.is_some() {
+                voided += 1;
+            }
+        }
+        Ok(voided)
+    }
+
+    /// Override capture-on-fulfillment for an order; None follows the config
+    pub async fn set_capture_on_fulfillment(&self, order_id: Uuid, enabled: Option<bool>) -> Result<()> {
+        if !self.repository.set_capture_on_fulfillment(order_id, enabled).await? {
+            return Err(Error::not_found("Order not found"));
+        }
+        Ok(())
+    }
+
+    async fn capture_authorization(
+        &self,
+        authorization: &PaymentAuthorization,
+        amount: Decimal,
+        final_capture: bool,
+        source: CaptureSource,
+        shipment_id: Option<Uuid>,
+        captured_by: Option<Uuid>,
+    ) -> Result<PaymentCapture> {
+        let (gateway, gateway_payment_id) = self.gateway(authorization)?;
+        let captured = gateway.capture_payment(gateway_payment_id, amount, final_capture).await?;
+        let capture = NewPaymentCapture {
+            amount: captured.amount,
+            gateway_capture_id: Some(captured.capture_id),
+            final_capture,
+            source,
+            shipment_id,
+            captured_by,
+        };
+        self.repository.record_capture(authorization.id, &capture).await
+    }
+
+    fn gateway<'a>(&'a self, authorization: &'a PaymentAuthorization) -> Result<(&'a dyn AgnosticPaymentGateway, &'a str)> {
+        let gateway = self
+            .payments
+            .get_gateway(Some(&authorization.gateway))
+            .ok_or_else(|| Error::payment_error(format!("Payment gateway '{}' not configured", authorization.gateway)))?;
+        let gateway_payment_id = authorization
+            .gateway_payment_id
+            .as_deref()
+            .ok_or_else(|| Error::validation("The payment has no gateway payment ID"))?;
+        Ok((gateway, gateway_payment_id))
+    }
+}
+
+/// Validate a requested capture; defaults to what is left
+fn capture_amount(
+    authorization: &PaymentAuthorization,
+    requested: Option<Decimal>,
+    now: DateTime<Utc>,
+) -> Result<Decimal> {
+    if !authorization.is_open() {
+        return Err(Error::validation("The payment is no longer authorized"));
+    }
+    if authorization.is_expired(now) {
+        return Err(Error::validation("The authorization has expired"));
+    }
+    let remaining = authorization.remaining();
+    let amount = requested.unwrap_or(remaining);
+    if amount <= Decimal::ZERO {
+        return Err(Error::validation("Capture amount must be positive"));
+    }
+    if amount > remaining {
+        return Err(Error::validation(format!(
+            "Capture amount {} exceeds the {} left on the authorization",
+            amount, remaining
+        )));
+    }
+    Ok(amount)
+}
+
+/// What a shipment captures: its items, or everything left once the order
+/// has fully shipped (shipping, taxes)
+fn shipment_amount(value: Decimal, remaining: Decimal, fully_shipped: bool) -> Decimal {
+    if fully_shipped {
+        remaining
+    } else {
+        value.min(remaining)
+    }
+}
+
+/// Voids authorizations that lapsed before they were captured
+pub struct AuthorizationExpiryJob<R: PaymentCaptureRepository> {
+    captures: Arc<PaymentCaptureService<R>>,
+}
+
+impl<R: PaymentCaptureRepository> AuthorizationExpiryJob<R> {
+    pub fn new(captures: Arc<PaymentCaptureService<R>>) -> Self {
+        Self { captures }
+    }
+}
+
+#[async_trait]
+impl<R: PaymentCaptureRepository + 'static> RecurringJob for AuthorizationExpiryJob<R> {
+    fn name(&self) -> &str {
+        "authorization_expiry"
+    }
+
+    fn description(&self) -> &str {
+        "Void payment authorizations that expired before they were captured"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.captures.config().expiry_check_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        let voided = self.captures.void_expired().await?;
+        Ok(format!("voided {} expired authorizations", voided))
+    }
+}
+
+/// Captures shipments of orders that capture on fulfillment
+pub struct CaptureOnShipment<R: PaymentCaptureRepository> {
+    captures: Arc<PaymentCaptureService<R>>,
+}
+
+impl<R: PaymentCaptureRepository> CaptureOnShipment<R> {
+    pub fn new(captures: Arc<PaymentCaptureService<R>>) -> Self {
+        Self { captures }
+    }
+}
+
+#[async_trait]
+impl<R: PaymentCaptureRepository + 'static> EventHandler for CaptureOnShipment<R> {
+    fn name(&self) -> &'static str {
+        "capture_on_shipment"
+    }
+
+    async fn handle(&self, event: &BusEvent) -> Result<()> {
+        let shipment = match &event.event {
+            DomainEvent::ShipmentStatusChanged(shipment)
+                if matches!(shipment.status.as_str(), "shipped" | "delivered") =>
+            {
+                shipment
+            }
+            _ => return Ok(()),
+        };
+        if let Some(capture) = self.captures.capture_shipment(shipment.shipment_id).await? {
+            tracing::info!(
+                "Captured {} {} of payment {} for shipment {}",
+                capture.amount,
+                capture.currency,
+                capture.payment_id,
+                shipment.shipment_id
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rust_decimal_macros::dec;
+
+    fn authorization(captured: Decimal) -> PaymentAuthorization {
+        let now = Utc::now();
+        PaymentAuthorization {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            gateway: "stripe".to_string(),
+            gateway_payment_id: Some("pi_1".to_string()),
+            currency: "USD".to_string(),
+            status: "authorized".to_string(),
+            authorized_amount: dec!(100.00),
+            captured_amount: captured,
+            authorization_expires_at: Some(now + Duration::days(7)),
+            voided_at: None,
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn test_capture_amount() {
+        let now = Utc::now();
+        let open = authorization(dec!(30.00));
+        assert_eq!(capture_amount(&open, None, now).unwrap(), dec!(70.00));
+        assert_eq!(capture_amount(&open, Some(dec!(25.00)), now).unwrap(), dec!(25.00));
+        assert!(capture_amount(&open, Some(dec!(70.01)), now).is_err());
+        assert!(capture_amount(&open, Some(Decimal::ZERO), now).is_err());
+        assert!(capture_amount(&open, None, now + Duration::days(8)).is_err());
+
+        let mut voided = authorization(dec!(30.00));
+        voided.status = "paid".to_string();
+        assert!(capture_amount(&voided, None, now).is_err());
+    }
+
+    #[test]
+    fn test_shipment_amount() {
+        assert_eq!(shipment_amount(dec!(40.00), dec!(100.00), false), dec!(40.00));
+        assert_eq!(shipment_amount(dec!(40.00), dec!(25.00), false), dec!(25.00));
+        // The last shipment also captures shipping and taxes
+        assert_eq!(shipment_amount(dec!(40.00), dec!(55.00), true), dec!(55.00));
+    }
+}
@@ -0,0 +1,468 @@
+//! Payment Session Service
+//!
+//! Follows payments started through the payments API until they settle
+//! (see `models::payment_session`). Card payments that need Strong Customer
+//! Authentication answer with a `three_d_secure` action holding a
+//! `ThreeDsChallenge`, shown the way the storefront asked (redirect or
+//! iframe). The gateway sends the customer back to this API's return
+//! endpoint, which completes the payment and redirects to the storefront;
+//! storefronts can also poll the session. Succeeded payments are recorded
+//! against their order; with `payment.capture.method = "manual"` payments
+//! are only authorized, for `PaymentCaptureService` to capture.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::config::{PaymentCaptureConfig, ThreeDSecureConfig};
+use crate::models::{NewPaymentSession, PaymentSession, PaymentSessionStatus, PaymentSessionUpdate};
+use crate::payment::agnostic::{
+    AgnosticPaymentGateway, ChallengeDisplay, CompletePaymentActionRequest, CompletePaymentActionResponse,
+    InitiatePaymentRequest, InitiatePaymentResponse, PaymentActionType, PaymentService, PaymentStatus,
+    ThreeDsChallenge,
+};
+use crate::repository::PaymentSessionRepository;
+use crate::{Error, Result};
+
+/// Payment session service
+pub struct PaymentSessionService<R: PaymentSessionRepository> {
+    repository: R,
+    payments: Arc<PaymentService>,
+    config: ThreeDSecureConfig,
+    capture: PaymentCaptureConfig,
+}
+
+impl<R: PaymentSessionRepository> PaymentSessionService<R> {
+    pub fn new(repository: R, payments: Arc<PaymentService>, config: ThreeDSecureConfig) -> Self {
+        Self {
+            repository,
+            payments,
+            config,
+            capture: PaymentCaptureConfig::default(),
+        }
+    }
+
+    /// Authorize payments only, per `payment.capture`
+    pub fn with_capture(mut self, capture: PaymentCaptureConfig) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Start a payment in a new session; after a challenge the customer
+    /// lands on `return_url` (or `payment.three_d_secure.return_url`)
+    pub async fn initiate(
+        &self,
+        gateway_id: &str,
+        mut request: InitiatePaymentRequest,
+        return_url: Option<String>,
+        display: ChallengeDisplay,
+    ) -> Result<(PaymentSession, InitiatePaymentResponse)> {
+        let return_url = return_url_for(
+            return_url,
+            self.config.return_url.as_deref(),
+            &self.config.allowed_return_hosts,
+        )?;
+        let gateway = self
+            .payments
+            .get_gateway(Some(gateway_id))
+            .ok_or_else(|| Error::validation(format!("Gateway '{}' not found", gateway_id)))?;
+
+        let session = self
+            .repository
+            .create(&NewPaymentSession {
+                order_id: request.order_id,
+                gateway: gateway_id.to_string(),
+                amount: request.amount,
+                currency: request.currency.clone(),
+                return_url,
+            })
+            .await?;
+        request.return_url = Some(self.callback_url(session.id));
+        request.capture_method = self.capture.method;
+
+        // Decrypt wallet tokens the gateway can't take, then charge
+        let result = async {
+            let request = self.payments.prepare_payment(gateway, request).await?;
+            gateway.initiate_payment(request).await
+        }
+        .await;
+        let response = match result {
+            Ok(response) => with_challenge(response, display, self.challenge_expires_at()),
+            Err(e) => {
+                self.repository
+                    .update(session.id, &PaymentSessionUpdate::failed("payment_error", e.to_string()))
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        let session = self.save(session, initiated(&response)).await?;
+        Ok((session, response))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<PaymentSession> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Payment session not found"))
+    }
+
+    /// Complete a session's pending action, e.g. when the gateway sends the
+    /// customer back after a challenge; settled sessions are left as they are
+    pub async fn complete(&self, id: Uuid, action_data: serde_json::Value) -> Result<PaymentSession> {
+        let session = self.get(id).await?;
+        if session.status.is_final() {
+            return Ok(session);
+        }
+        if session.is_expired(Utc::now()) {
+            return self.save(session, PaymentSessionUpdate::status(PaymentSessionStatus::Expired)).await;
+        }
+        let payment_id = session
+            .gateway_payment_id
+            .clone()
+            .ok_or_else(|| Error::validation("Payment session has no gateway payment yet"))?;
+        let action_type = session
+            .action_type
+            .as_deref()
+            .and_then(parse_action_type)
+            .unwrap_or(PaymentActionType::ThreeDSecure);
+        let request = CompletePaymentActionRequest {
+            payment_id,
+            action_type,
+            action_data,
+        };
+        Ok(self.complete_action(session, request).await?.0)
+    }
+
+    /// Complete a payment's pending action by its gateway payment ID
+    pub async fn complete_payment(&self, request: CompletePaymentActionRequest) -> Result<CompletePaymentActionResponse> {
+        match self.repository.find_by_payment(&request.payment_id).await? {
+            Some(session) => Ok(self.complete_action(session, request).await?.1),
+            // Payments started before sessions were kept
+            None => self.gateway(None)?.complete_payment_action(request).await,
+        }
+    }
+
+    async fn complete_action(
+        &self,
+        session: PaymentSession,
+        request: CompletePaymentActionRequest,
+    ) -> Result<(PaymentSession, CompletePaymentActionResponse)> {
+        let display = session
+            .action_data
+            .as_ref()
+            .and_then(ThreeDsChallenge::from_action_data)
+            .map(|challenge| challenge.display)
+            .unwrap_or_default();
+        let response = self
+            .gateway(Some(&session.gateway))?
+            .complete_payment_action(request)
+            .await?;
+        let response = with_next_challenge(response, display);
+        let session = self.save(session, completed(&response)).await?;
+        Ok((session, response))
+    }
+
+    /// A session brought up to date with its gateway, for storefronts
+    /// polling after a challenge
+    pub async fn poll(&self, id: Uuid) -> Result<PaymentSession> {
+        let session = self.get(id).await?;
+        self.refresh(session).await
+    }
+
+    /// The session of a gateway payment brought up to date; None for
+    /// payments started before sessions were kept
+    pub async fn poll_payment(&self, payment_id: &str) -> Result<Option<PaymentSession>> {
+        match self.repository.find_by_payment(payment_id).await? {
+            Some(session) => self.refresh(session).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn refresh(&self, session: PaymentSession) -> Result<PaymentSession> {
+        if session.status.is_final() {
+            return Ok(session);
+        }
+        if session.is_expired(Utc::now()) {
+            return self.save(session, PaymentSessionUpdate::status(PaymentSessionStatus::Expired)).await;
+        }
+        let Some(payment_id) = session.gateway_payment_id.as_deref() else {
+            return Ok(session);
+        };
+        let status = session_status(
+            &self
+                .gateway(Some(&session.gateway))?
+                .get_payment_status(payment_id)
+                .await?,
+        );
+        if status == session.status {
+            return Ok(session);
+        }
+
+        // Still waiting for the customer: keep the challenge
+        let mut update = PaymentSessionUpdate::status(status);
+        if status == PaymentSessionStatus::RequiresAction {
+            update.action_type = session.action_type.clone();
+            update.action_data = session.action_data.clone();
+        }
+        self.save(session, update).await
+    }
+
+    async fn save(&self, session: PaymentSession, mut update: PaymentSessionUpdate) -> Result<PaymentSession> {
+        if update.status == PaymentSessionStatus::Authorized {
+            update.authorization_expires_at =
+                Some(Utc::now() + Duration::days(i64::from(self.capture.authorization_valid_days)));
+        }
+        match self.repository.update(session.id, &update).await? {
+            Some(session) => Ok(session),
+            // Settled meanwhile
+            None => self.get(session.id).await,
+        }
+    }
+
+    fn gateway(&self, gateway_id: Option<&str>) -> Result<&dyn AgnosticPaymentGateway> {
+        self.payments.get_gateway(gateway_id).ok_or_else(|| {
+            Error::payment_error(format!(
+                "Payment gateway '{}' is not configured",
+                gateway_id.unwrap_or(self.payments.default_gateway())
+            ))
+        })
+    }
+
+    /// Where gateways send customers back after a challenge
+    fn callback_url(&self, id: Uuid) -> String {
+        format!(
+            "{}/api/v1/payments/sessions/{}/return",
+            self.config.public_url.trim_end_matches('/'),
+            id
+        )
+    }
+
+    fn challenge_expires_at(&self) -> DateTime<Utc> {
+        Utc::now() + Duration::minutes(i64::from(self.config.challenge_expires_mins))
+    }
+}
+
+/// A requested return URL, which must point at an allowed host, or else
+/// the configured one
+fn return_url_for(requested: Option<String>, configured: Option<&str>, allowed_hosts: &[String]) -> Result<Option<String>> {
+    let Some(url) = requested else {
+        return Ok(configured.map(str::to_string));
+    };
+    let parsed = url::Url::parse(&url)
+        .ok()
+        .filter(|parsed| matches!(parsed.scheme(), "https" | "http"))
+        .ok_or_else(|| Error::validation("return_url must be an http(s) URL"))?;
+    let host = parsed.host_str().unwrap_or_default();
+    if !allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)) {
+        return Err(Error::validation(
+            "return_url must point at one of payment.three_d_secure.allowed_return_hosts",
+        ));
+    }
+    Ok(Some(url))
+}
+
+/// The response with its 3-D Secure challenge in `ThreeDsChallenge` form,
+/// shown as asked, lapsing by `expires_at` at the latest
+fn with_challenge(
+    response: InitiatePaymentResponse,
+    display: ChallengeDisplay,
+    expires_at: DateTime<Utc>,
+) -> InitiatePaymentResponse {
+    match response {
+        InitiatePaymentResponse::RequiresAction {
+            payment_id,
+            action_type,
+            action_data,
+            expires_at: gateway_expires_at,
+        } => InitiatePaymentResponse::RequiresAction {
+            payment_id,
+            action_data: challenge_data(&action_type, action_data, display),
+            action_type,
+            expires_at: gateway_expires_at.min(expires_at),
+        },
+        response => response,
+    }
+}
+
+fn with_next_challenge(response: CompletePaymentActionResponse, display: ChallengeDisplay) -> CompletePaymentActionResponse {
+    match response {
+        CompletePaymentActionResponse::RequiresAction {
+            payment_id,
+            action_type,
+            action_data,
+        } => CompletePaymentActionResponse::RequiresAction {
+            payment_id,
+            action_data: challenge_data(&action_type, action_data, display),
+            action_type,
+        },
+        response => response,
+    }
+}
+
+fn challenge_data(action_type: &PaymentActionType, action_data: serde_json::Value, display: ChallengeDisplay) -> serde_json::Value {
+    if !matches!(action_type, PaymentActionType::ThreeDSecure) {
+        return action_data;
+    }
+    match ThreeDsChallenge::from_action_data(&action_data) {
+        Some(challenge) => challenge.displayed_as(display).action_data(),
+        None => action_data,
+    }
+}
+
+/// Session update for the answer to a new payment
+fn initiated(response: &InitiatePaymentResponse) -> PaymentSessionUpdate {
+    match response {
+        InitiatePaymentResponse::RequiresAction {
+            payment_id,
+            action_type,
+            action_data,
+            expires_at,
+        } => PaymentSessionUpdate {
+            gateway_payment_id: Some(payment_id.clone()),
+            action_type: Some(action_name(action_type)),
+            action_data: Some(action_data.clone()),
+            expires_at: Some(*expires_at),
+            ..PaymentSessionUpdate::status(PaymentSessionStatus::RequiresAction)
+        },
+        InitiatePaymentResponse::Success {
+            payment_id,
+            payment_status,
+            receipt_url,
+            ..
+        } => PaymentSessionUpdate {
+            gateway_payment_id: Some(payment_id.clone()),
+            receipt_url: receipt_url.clone(),
+            ..PaymentSessionUpdate::status(session_status(payment_status))
+        },
+        InitiatePaymentResponse::Failed {
+            payment_id,
+            error_code,
+            error_message,
+            ..
+        } => PaymentSessionUpdate {
+            gateway_payment_id: Some(payment_id.clone()),
+            ..PaymentSessionUpdate::failed(error_code, error_message)
+        },
+    }
+}
+
+/// Session update for the answer to a completed action
+fn completed(response: &CompletePaymentActionResponse) -> PaymentSessionUpdate {
+    match response {
+        CompletePaymentActionResponse::RequiresAction {
+            action_type,
+            action_data,
+            ..
+        } => PaymentSessionUpdate {
+            action_type: Some(action_name(action_type)),
+            action_data: Some(action_data.clone()),
+            ..PaymentSessionUpdate::status(PaymentSessionStatus::RequiresAction)
+        },
+        CompletePaymentActionResponse::Success {
+            payment_status,
+            receipt_url,
+            ..
+        } => PaymentSessionUpdate {
+            receipt_url: receipt_url.clone(),
+            ..PaymentSessionUpdate::status(session_status(payment_status))
+        },
+        CompletePaymentActionResponse::Failed {
+            error_code,
+            error_message,
+            ..
+        } => PaymentSessionUpdate::failed(error_code, error_message),
+    }
+}
+
+/// Session status of a gateway payment status
+fn session_status(status: &PaymentStatus) -> PaymentSessionStatus {
+    match status {
+        PaymentStatus::Pending => PaymentSessionStatus::Pending,
+        PaymentStatus::Processing => PaymentSessionStatus::Processing,
+        PaymentStatus::RequiresAction => PaymentSessionStatus::RequiresAction,
+        PaymentStatus::Authorized => PaymentSessionStatus::Authorized,
+        PaymentStatus::Succeeded
+        | PaymentStatus::Refunded
+        | PaymentStatus::PartiallyRefunded
+        | PaymentStatus::Disputed => PaymentSessionStatus::Succeeded,
+        PaymentStatus::Failed => PaymentSessionStatus::Failed,
+        PaymentStatus::Cancelled => PaymentSessionStatus::Cancelled,
+    }
+}
+
+/// `three_d_secure`, `redirect`, ...
+fn action_name(action_type: &PaymentActionType) -> String {
+    serde_json::to_value(action_type)
+        .ok()
+        .and_then(|name| name.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_action_type(name: &str) -> Option<PaymentActionType> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_return_url_for() {
+        let hosts = vec!["shop.example.com".to_string()];
+        assert_eq!(return_url_for(None, None, &hosts).unwrap(), None);
+        assert_eq!(
+            return_url_for(None, Some("https://shop.example.com/pay"), &[]).unwrap().as_deref(),
+            Some("https://shop.example.com/pay")
+        );
+        assert!(return_url_for(Some("https://shop.example.com/done".to_string()), None, &hosts).is_ok());
+        assert!(return_url_for(Some("https://evil.example.net/".to_string()), None, &hosts).is_err());
+        assert!(return_url_for(Some("javascript:alert(1)".to_string()), None, &hosts).is_err());
+    }
+
+    #[test]
+    fn test_challenge_in_response() {
+        let expires_at = Utc::now() + Duration::minutes(30);
+        let response = with_challenge(
+            InitiatePaymentResponse::RequiresAction {
+                payment_id: "pi_1".to_string(),
+                action_type: PaymentActionType::ThreeDSecure,
+                action_data: serde_json::json!({"redirect_url": "https://acs.example.com/c"}),
+                expires_at: expires_at + Duration::hours(1),
+            },
+            ChallengeDisplay::Iframe,
+            expires_at,
+        );
+
+        let update = initiated(&response);
+        assert_eq!(update.status, PaymentSessionStatus::RequiresAction);
+        assert_eq!(update.gateway_payment_id.as_deref(), Some("pi_1"));
+        assert_eq!(update.action_type.as_deref(), Some("three_d_secure"));
+        assert_eq!(update.expires_at, Some(expires_at));
+        let challenge = ThreeDsChallenge::from_action_data(update.action_data.as_ref().unwrap()).unwrap();
+        assert_eq!(challenge.display, ChallengeDisplay::Iframe);
+        assert_eq!(challenge.url.as_deref(), Some("https://acs.example.com/c"));
+        assert_eq!(parse_action_type("three_d_secure").map(|t| action_name(&t)).as_deref(), Some("three_d_secure"));
+    }
+
+    #[test]
+    fn test_completed_updates() {
+        let update = completed(&CompletePaymentActionResponse::Failed {
+            payment_id: "pi_1".to_string(),
+            error_code: "authentication_failed".to_string(),
+            error_message: "The customer failed 3-D Secure".to_string(),
+            retry_allowed: true,
+        });
+        assert_eq!(update.status, PaymentSessionStatus::Failed);
+        assert_eq!(update.error_code.as_deref(), Some("authentication_failed"));
+
+        assert_eq!(session_status(&PaymentStatus::Succeeded), PaymentSessionStatus::Succeeded);
+        assert_eq!(session_status(&PaymentStatus::Processing), PaymentSessionStatus::Processing);
+        assert_eq!(session_status(&PaymentStatus::Authorized), PaymentSessionStatus::Authorized);
+    }
+}
@@ -0,0 +1,241 @@
+//! Payout Reconciliation Service
+//!
+//! Imports the payouts gateways made (`payment.reconciliation.gateways`)
+//! with the balance transactions each paid out, and matches every charge
+//! and refund against the payment or refund recorded under its gateway ID.
+//! Transactions nothing was recorded for, or recorded at another amount,
+//! are flagged; fees and adjustments are kept apart. Re-importing a payout
+//! matches it again, so refunds recorded late clear their flags.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::config::PayoutReconciliationConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::models::{
+    Payout, PayoutDetail, PayoutImport, ReconciledLine, ReconciliationReport, ReconciliationStatus,
+    RecordedTransaction,
+};
+use crate::payment::agnostic::{PaymentService, SettlementKind, SettlementTransaction};
+use crate::reports::SalesRange;
+use crate::repository::PayoutRepository;
+use crate::{Error, Result};
+
+/// Flagged lines and unsettled payments listed at most in a report
+const REPORT_LIMIT: i64 = 500;
+
+/// Payout reconciliation service
+pub struct PayoutReconciliationService<R: PayoutRepository> {
+    repository: R,
+    payments: Arc<PaymentService>,
+    config: PayoutReconciliationConfig,
+}
+
+impl<R: PayoutRepository> PayoutReconciliationService<R> {
+    pub fn new(repository: R, payments: Arc<PaymentService>, config: PayoutReconciliationConfig) -> Self {
+        Self { repository, payments, config }
+    }
+
+    pub fn config(&self) -> &PayoutReconciliationConfig {
+        &self.config
+    }
+
+    /// Import and match the payouts of the last `lookback_days`. A gateway
+    /// that fails is reported and the others still imported.
+    pub async fn import(&self) -> Result<PayoutImport> {
+        let since = Utc::now() - Duration::days(self.config.lookback_days as i64);
+        let mut import = PayoutImport::default();
+        for gateway_id in &self.config.gateways {
+            let Some(gateway) = self.payments.get_gateway(Some(gateway_id)) else {
+                tracing::warn!("Payout reconciliation skips gateway '{}': not configured", gateway_id);
+                import.failed_gateways.push(gateway_id.clone());
+                continue;
+            };
+            let payouts = match gateway.list_payouts(since).await {
+                Ok(payouts) => payouts,
+                Err(e) => {
+                    tracing::warn!("Failed to list payouts of gateway '{}': {}", gateway_id, e);
+                    import.failed_gateways.push(gateway_id.clone());
+                    continue;
+                }
+            };
+            for payout in payouts {
+                let mut lines = Vec::with_capacity(payout.transactions.len());
+                for transaction in &payout.transactions {
+                    lines.push(self.reconcile(gateway_id, transaction).await?);
+                }
+                import.lines += lines.len();
+                import.flagged += lines.iter().filter(|line| line.status.is_flagged()).count();
+                self.repository.save_payout(gateway_id, &payout, &lines).await?;
+                import.payouts += 1;
+            }
+        }
+        Ok(import)
+    }
+
+    pub async fn payouts(&self, gateway: Option<&str>, limit: i64, offset: i64) -> Result<Vec<Payout>> {
+        self.repository.list_payouts(gateway, limit, offset).await
+    }
+
+    pub async fn payout(&self, id: Uuid) -> Result<PayoutDetail> {
+        let payout = self
+            .repository
+            .find_payout(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Payout not found"))?;
+        let lines = self.repository.payout_lines(id).await?;
+        Ok(PayoutDetail { payout, lines })
+    }
+
+    /// Reconciliation of the payouts arriving in `range`
+    pub async fn report(&self, range: SalesRange, gateway: Option<&str>) -> Result<ReconciliationReport> {
+        let from = range.from.and_time(NaiveTime::MIN).and_utc();
+        let to = (range.to + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+        let totals = self.repository.totals(from, to, gateway).await?;
+        let lines = self.repository.status_counts(from, to, gateway).await?;
+        let flagged = self.repository.flagged_lines(from, to, gateway, REPORT_LIMIT).await?;
+
+        // Payments taken lately may simply not be paid out yet
+        let settled_by = Utc::now() - Duration::days(self.config.settlement_days as i64);
+        let gateways = match gateway {
+            Some(gateway) => vec![gateway.to_string()],
+            None => self.config.gateways.clone(),
+        };
+        let unsettled = self
+            .repository
+            .unsettled_payments(&gateways, from, to.min(settled_by), REPORT_LIMIT)
+            .await?;
+
+        Ok(ReconciliationReport {
+            from: range.from,
+            to: range.to,
+            gateway: gateway.map(str::to_string),
+            totals,
+            lines,
+            flagged,
+            unsettled,
+        })
+    }
+
+    async fn reconcile(&self, gateway: &str, transaction: &SettlementTransaction) -> Result<ReconciledLine> {
+        let recorded = match (transaction.kind, transaction.reference.as_deref()) {
+            (SettlementKind::Charge, Some(reference)) => self.repository.find_payment(gateway, reference).await?,
+            (SettlementKind::Refund, Some(reference)) => self.repository.find_refund(gateway, reference).await?,
+            _ => None,
+        };
+        Ok(ReconciledLine {
+            status: line_status(transaction, recorded.as_ref(), self.config.amount_tolerance),
+            transaction: transaction.clone(),
+            recorded,
+        })
+    }
+}
+
+/// How a paid out transaction compares to what was recorded. Amounts are
+/// only compared in the same currency; refunds are paid out negative.
+fn line_status(
+    transaction: &SettlementTransaction,
+    recorded: Option<&RecordedTransaction>,
+    tolerance: Decimal,
+) -> ReconciliationStatus {
+    match transaction.kind {
+        SettlementKind::Fee => return ReconciliationStatus::Fee,
+        SettlementKind::Adjustment => return ReconciliationStatus::Adjustment,
+        SettlementKind::Charge | SettlementKind::Refund => {}
+    }
+    let Some(recorded) = recorded else {
+        return ReconciliationStatus::Unmatched;
+    };
+    if !recorded.currency.eq_ignore_ascii_case(&transaction.currency) {
+        return ReconciliationStatus::Matched;
+    }
+    if (transaction.amount.abs() - recorded.amount).abs() > tolerance {
+        ReconciliationStatus::AmountMismatch
+    } else {
+        ReconciliationStatus::Matched
+    }
+}
+
+/// Imports gateway payouts and matches them against recorded payments
+pub struct PayoutReconciliationJob<R: PayoutRepository> {
+    reconciliation: Arc<PayoutReconciliationService<R>>,
+}
+
+impl<R: PayoutRepository> PayoutReconciliationJob<R> {
+    pub fn new(reconciliation: Arc<PayoutReconciliationService<R>>) -> Self {
+        Self { reconciliation }
+    }
+}
+
+#[async_trait]
+impl<R: PayoutRepository + 'static> RecurringJob for PayoutReconciliationJob<R> {
+    fn name(&self) -> &str {
+        "payout_reconciliation"
+    }
+
+    fn description(&self) -> &str {
+        "Import gateway payouts and match them against recorded payments and refunds"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.reconciliation.config().interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        let import = self.reconciliation.import().await?;
+        let mut summary = format!(
+            "imported {} payouts with {} transactions, {} flagged",
+            import.payouts, import.lines, import.flagged
+        );
+        if !import.failed_gateways.is_empty() {
+            summary.push_str(&format!("; failed: {}", import.failed_gateways.join(", ")));
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn transaction(kind: SettlementKind, amount: Decimal) -> SettlementTransaction {
+        SettlementTransaction {
+            transaction_id: "txn_1".to_string(),
+            kind,
+            reference: Some("pi_1".to_string()),
+            amount,
+            fee: Decimal::ZERO,
+            net: amount,
+            currency: "EUR".to_string(),
+            description: None,
+        }
+    }
+
+    fn recorded(amount: Decimal, currency: &str) -> RecordedTransaction {
+        RecordedTransaction { id: Uuid::new_v4(), amount, currency: currency.to_string() }
+    }
+
+    #[test]
+    fn test_line_status() {
+        let charge = transaction(SettlementKind::Charge, dec!(25.00));
+        assert_eq!(line_status(&charge, Some(&recorded(dec!(25.00), "EUR")), Decimal::ZERO), ReconciliationStatus::Matched);
+        assert_eq!(line_status(&charge, Some(&recorded(dec!(24.99), "EUR")), Decimal::ZERO), ReconciliationStatus::AmountMismatch);
+        assert_eq!(line_status(&charge, Some(&recorded(dec!(24.99), "EUR")), dec!(0.01)), ReconciliationStatus::Matched);
+        // Converted at the gateway
+        assert_eq!(line_status(&charge, Some(&recorded(dec!(27.10), "USD")), Decimal::ZERO), ReconciliationStatus::Matched);
+        assert_eq!(line_status(&charge, None, Decimal::ZERO), ReconciliationStatus::Unmatched);
+
+        let refund = transaction(SettlementKind::Refund, dec!(-10.00));
+        assert_eq!(line_status(&refund, Some(&recorded(dec!(10.00), "EUR")), Decimal::ZERO), ReconciliationStatus::Matched);
+
+        let fee = transaction(SettlementKind::Fee, dec!(-5.00));
+        assert_eq!(line_status(&fee, None, Decimal::ZERO), ReconciliationStatus::Fee);
+        assert!(!ReconciliationStatus::Fee.is_flagged());
+        assert!(ReconciliationStatus::Unmatched.is_flagged());
+    }
+}
@@ -0,0 +1,282 @@
+//! Price List Service
+//!
+//! Manages per-currency price lists and resolves what a product costs in a
+//! shopper's currency and country:
+//!
+//! 1. the best matching active price list price (country-specific lists
+//!    first, then by priority; a variant's own price before its product's)
+//! 2. the product's own price, when it is already in that currency
+//! 3. the product's own price converted with the configured FX provider
+//!
+//! For a customer in a group, the group's own price for the quantity (in
+//! the product's currency, converted like its base price) replaces all of
+//! these, and otherwise the group's discount comes off the resolved price.
+//!
+//! It also builds `OrderCalculator`s for orders in a currency other than the
+//! base currency.
+
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::fx::{convert_amount, FxRateProvider};
+use crate::models::{
+    normalize_countries, CreatePriceListRequest, Currency, CustomerGroupPricing, PriceList, PriceListPrice, PriceSource, ResolvedPrice,
+    SetPriceListPriceRequest, UpdatePriceListRequest,
+};
+use crate::order::{CurrencyConversion, OrderCalculator};
+use crate::repository::PriceListRepository;
+use crate::{Error, Result};
+
+/// Price list service
+pub struct PriceListService<R: PriceListRepository> {
+    repository: R,
+    fx: Arc<dyn FxRateProvider>,
+    base_currency: Currency,
+}
+
+impl<R: PriceListRepository> PriceListService<R> {
+    pub fn new(repository: R, fx: Arc<dyn FxRateProvider>, base_currency: Currency) -> Self {
+        Self {
+            repository,
+            fx,
+            base_currency,
+        }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn base_currency(&self) -> Currency {
+        self.base_currency
+    }
+
+    /// The configured FX rate provider
+    pub fn fx(&self) -> &Arc<dyn FxRateProvider> {
+        &self.fx
+    }
+
+    /// Create a price list
+    pub async fn create_list(&self, request: CreatePriceListRequest) -> Result<PriceList> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let countries = normalize_countries(&request.countries).map_err(Error::validation)?;
+        self.repository.create(&request, &countries).await
+    }
+
+    /// Update a price list
+    pub async fn update_list(&self, id: Uuid, request: UpdatePriceListRequest) -> Result<PriceList> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let countries = request
+            .countries
+            .as_deref()
+            .map(normalize_countries)
+            .transpose()
+            .map_err(Error::validation)?;
+        self.repository
+            .update(id, &request, countries.as_deref())
+            .await?
+            .ok_or_else(|| Error::not_found("Price list not found"))
+    }
+
+    /// Get a price list
+    pub async fn get_list(&self, id: Uuid) -> Result<PriceList> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Price list not found"))
+    }
+
+    /// Set a product or variant price on a list
+    pub async fn set_price(&self, price_list_id: Uuid, request: SetPriceListPriceRequest) -> Result<PriceListPrice> {
+        if request.price < Decimal::ZERO || request.compare_at_price.is_some_and(|p| p < Decimal::ZERO) {
+            return Err(Error::validation("Prices cannot be negative"));
+        }
+        self.get_list(price_list_id).await?;
+        if self.repository.base_price(request.product_id, request.variant_id).await?.is_none() {
+            return Err(Error::not_found("Product or variant not found"));
+        }
+        self.repository.set_price(price_list_id, &request).await
+    }
+
+    /// Remove a price from a list
+    pub async fn remove_price(&self, price_list_id: Uuid, price_id: Uuid) -> Result<()> {
+        if !self.repository.delete_price(price_list_id, price_id).await? {
+            return Err(Error::not_found("Price not found on this price list"));
+        }
+        Ok(())
+    }
+
+    /// Price of a product (or variant) in a currency, for a shopper in `country`
+    pub async fn resolve_price(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        currency: Currency,
+        country: Option<&str>,
+    ) -> Result<ResolvedPrice> {
+        if let Some(listed) = self.repository.best_price(product_id, variant_id, currency, country).await? {
+            return Ok(ResolvedPrice {
+                product_id,
+                variant_id,
+                currency,
+                price: listed.price,
+                compare_at_price: listed.compare_at_price,
+                source: PriceSource::PriceList { price_list_id: listed.price_list_id },
+            });
+        }
+
+        let base = self
+            .repository
+            .base_price(product_id, variant_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Product or variant not found"))?;
+        if base.currency == currency {
+            return Ok(ResolvedPrice {
+                product_id,
+                variant_id,
+                currency,
+                price: base.price,
+                compare_at_price: base.compare_at_price,
+                source: PriceSource::Base,
+            });
+        }
+
+        let rate = self.rate(base.currency, currency).await?;
+        Ok(ResolvedPrice {
+            product_id,
+            variant_id,
+            currency,
+            price: convert_amount(base.price, rate),
+            compare_at_price: base.compare_at_price.map(|p| convert_amount(p, rate)),
+            source: PriceSource::Converted {
+                from: base.currency,
+                rate,
+                provider: self.fx.name().to_string(),
+            },
+        })
+    }
+
+    /// Price of `quantity` of a product (or variant) for a customer in a
+    /// group; the regular price when `pricing` is None
+    pub async fn resolve_customer_price(
+        &self,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        currency: Currency,
+        country: Option<&str>,
+        quantity: i32,
+        pricing: Option<&CustomerGroupPricing>,
+    ) -> Result<ResolvedPrice> {
+        let regular = self.resolve_price(product_id, variant_id, currency, country).await?;
+        let Some(pricing) = pricing else {
+            return Ok(regular);
+        };
+
+        let price = match pricing.price_for(product_id, variant_id, quantity) {
+            Some(group_price) => {
+                let base = self
+                    .repository
+                    .base_price(product_id, variant_id)
+                    .await?
+                    .ok_or_else(|| Error::not_found("Product or variant not found"))?;
+                if base.currency == currency {
+                    group_price.price
+                } else {
+                    convert_amount(group_price.price, self.rate(base.currency, currency).await?)
+                }
+            }
+            None if pricing.group.discount_percent.is_zero() => return Ok(regular),
+            None => pricing.discounted(regular.price),
+        };
+        Ok(ResolvedPrice {
+            price,
+            compare_at_price: if price < regular.price {
+                Some(regular.price)
+            } else {
+                regular.compare_at_price
+            },
+            source: PriceSource::CustomerGroup {
+                customer_group_id: pricing.group.id,
+            },
+            ..regular
+        })
+    }
+
+    /// Order calculator for an order in `order_currency`; converts from the
+    /// base currency at the current rate when the currencies differ
+    pub async fn order_calculator(
+        &self,
+        tax_rate: Decimal,
+        shipping_rate: Decimal,
+        order_currency: Currency,
+    ) -> Result<OrderCalculator> {
+        let calculator = OrderCalculator::new(tax_rate, shipping_rate);
+        if order_currency == self.base_currency {
+            return Ok(calculator);
+        }
+
+        let rate = self.rate(self.base_currency, order_currency).await?;
+        Ok(calculator.with_conversion(CurrencyConversion {
+            base_currency: self.base_currency,
+            order_currency,
+            rate,
+        }))
+    }
+
+    async fn rate(&self, from: Currency, to: Currency) -> Result<Decimal> {
+        self.fx.rate(from, to).await?.ok_or_else(|| {
+            Error::validation(format!(
+                "No {} exchange rate from {} to {}",
+                self.fx.name(),
+                from,
+                to
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::PostgresPriceListRepository;
+    use async_trait::async_trait;
+    use rust_decimal_macros::dec;
+
+    struct FixedRates;
+
+    #[async_trait]
+    impl FxRateProvider for FixedRates {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn rate(&self, from: Currency, to: Currency) -> Result<Option<Decimal>> {
+            Ok(match (from, to) {
+                (Currency::USD, Currency::EUR) => Some(dec!(0.92)),
+                _ => None,
+            })
+        }
+    }
+
+    fn service() -> PriceListService<PostgresPriceListRepository> {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/rcommerce").unwrap();
+        PriceListService::new(PostgresPriceListRepository::new(pool), Arc::new(FixedRates), Currency::USD)
+    }
+
+    #[tokio::test]
+    async fn test_order_calculator_converts_other_currencies() {
+        let prices = service();
+
+        let usd = prices.order_calculator(dec!(0.08), dec!(5.00), Currency::USD).await.unwrap();
+        assert!(usd.conversion().is_none());
+
+        let eur = prices.order_calculator(dec!(0.08), dec!(5.00), Currency::EUR).await.unwrap();
+        assert_eq!(eur.conversion().unwrap().rate, dec!(0.92));
+        assert_eq!(eur.unit_price(dec!(10.00), None), dec!(9.20));
+
+        assert!(prices.order_calculator(dec!(0.08), dec!(5.00), Currency::JPY).await.is_err());
+    }
+}
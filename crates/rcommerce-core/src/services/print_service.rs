@@ -0,0 +1,294 @@
+//! Print Service
+//!
+//! Merges the shipping labels or packing slips of many shipments into one
+//! PDF laid out for a printer profile (`[printing]`), and tracks whether
+//! the print station printed it. Label pages carry what the carrier label
+//! shows (address, service, tracking number) and the link to the carrier's
+//! label document.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::config::{PaperSize, PrinterProfile, PrintingConfig};
+use crate::models::{
+    CreatePrintBatchRequest, PrintBatch, PrintBatchFilter, PrintBatchStatus, PrintDocumentKind, PrintableShipment,
+};
+use crate::repository::PrintBatchRepository;
+use crate::services::invoice_service::{documents_pdf, truncate, PageLayout};
+use crate::{Error, Result};
+
+/// Print service
+pub struct PrintService<R: PrintBatchRepository> {
+    repository: R,
+    config: PrintingConfig,
+}
+
+impl<R: PrintBatchRepository> PrintService<R> {
+    pub fn new(repository: R, config: PrintingConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Configured printer profiles
+    pub fn profiles(&self) -> &[PrinterProfile] {
+        &self.config.profiles
+    }
+
+    /// The profile named `name`
+    pub fn profile(&self, name: &str) -> Result<&PrinterProfile> {
+        self.config
+            .profile(name)
+            .ok_or_else(|| Error::validation(format!("Unknown printer profile '{}'", name)))
+    }
+
+    /// Generate a batch of the documents of the requested shipments
+    pub async fn create_batch(&self, request: CreatePrintBatchRequest, created_by: Option<Uuid>) -> Result<PrintBatch> {
+        let kind = request.document_kind.unwrap_or(PrintDocumentKind::ShippingLabel);
+        let profile_name = request.profile.clone().unwrap_or_else(|| match kind {
+            PrintDocumentKind::ShippingLabel => self.config.label_profile.clone(),
+            PrintDocumentKind::PackingSlip => self.config.document_profile.clone(),
+        });
+        let profile = self.profile(&profile_name)?.clone();
+
+        let shipment_ids = if request.unprinted {
+            if !request.shipment_ids.is_empty() {
+                return Err(Error::validation("List shipments or select unprinted ones, not both"));
+            }
+            let limit = request.limit.unwrap_or(self.config.max_batch_size).min(self.config.max_batch_size);
+            self.repository
+                .unprinted_shipments(request.location.as_deref(), limit as i64)
+                .await?
+        } else {
+            let mut ids = request.shipment_ids.clone();
+            let mut seen = std::collections::HashSet::new();
+            ids.retain(|id| seen.insert(*id));
+            ids
+        };
+        if shipment_ids.is_empty() {
+            return Err(Error::validation("No shipments to print"));
+        }
+        if shipment_ids.len() > self.config.max_batch_size {
+            return Err(Error::validation(format!(
+                "A batch holds at most {} shipments",
+                self.config.max_batch_size
+            )));
+        }
+
+        let shipments = self.repository.printable(&shipment_ids).await?;
+        if shipments.len() < shipment_ids.len() {
+            let missing: Vec<String> = shipment_ids
+                .iter()
+                .filter(|id| !shipments.iter().any(|shipment| shipment.shipment_id == **id))
+                .map(Uuid::to_string)
+                .collect();
+            return Err(Error::not_found(format!("Shipments not found: {}", missing.join(", "))));
+        }
+        if kind == PrintDocumentKind::ShippingLabel {
+            let unlabelled: Vec<String> = shipments
+                .iter()
+                .filter(|shipment| shipment.label_url.is_none())
+                .map(|shipment| shipment.shipment_id.to_string())
+                .collect();
+            if !unlabelled.is_empty() {
+                return Err(Error::validation(format!(
+                    "Shipments have no label yet: {}",
+                    unlabelled.join(", ")
+                )));
+            }
+        }
+
+        let layout = page_layout(profile.paper);
+        let documents: Vec<Vec<String>> = shipments
+            .iter()
+            .map(|shipment| match kind {
+                PrintDocumentKind::ShippingLabel => label_lines(shipment, &layout),
+                PrintDocumentKind::PackingSlip => packing_slip_lines(shipment, &layout),
+            })
+            .collect();
+        let (pdf, pages) = documents_pdf(&documents, &layout);
+
+        self.repository
+            .create(kind, &profile.name, &shipment_ids, &pdf, pages as i32, created_by)
+            .await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<PrintBatch> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Print batch not found"))
+    }
+
+    pub async fn list(&self, filter: &PrintBatchFilter) -> Result<Vec<PrintBatch>> {
+        self.repository.list(filter).await
+    }
+
+    /// The merged PDF of a batch
+    pub async fn pdf(&self, id: Uuid) -> Result<Vec<u8>> {
+        self.repository
+            .pdf(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Print batch not found"))
+    }
+
+    /// Record that a batch was printed; label batches mark their shipments'
+    /// labels printed so `unprinted` selections skip them
+    pub async fn mark_printed(&self, id: Uuid) -> Result<PrintBatch> {
+        self.repository
+            .set_status(id, PrintBatchStatus::Printed, None, Utc::now())
+            .await?
+            .ok_or_else(|| Error::not_found("Print batch not found"))
+    }
+
+    /// Record that printing a batch failed
+    pub async fn mark_failed(&self, id: Uuid, error: &str) -> Result<PrintBatch> {
+        if error.trim().is_empty() {
+            return Err(Error::validation("Describe why printing failed"));
+        }
+        self.repository
+            .set_status(id, PrintBatchStatus::Failed, Some(error.trim()), Utc::now())
+            .await?
+            .ok_or_else(|| Error::not_found("Print batch not found"))
+    }
+}
+
+/// Page layout of a paper size
+pub(crate) fn page_layout(paper: PaperSize) -> PageLayout {
+    match paper {
+        PaperSize::Label4x6 => PageLayout { width: 288, height: 432, margin: 12, font_size: 9, line_height: 12 },
+        PaperSize::A4 => PageLayout::A4,
+        PaperSize::Letter => PageLayout { width: 612, height: 792, ..PageLayout::A4 },
+    }
+}
+
+fn address_lines(shipment: &PrintableShipment) -> Vec<String> {
+    let Some(address) = &shipment.ship_to else {
+        return vec!["(no shipping address)".to_string()];
+    };
+    let mut lines = vec![format!("{} {}", address.first_name, address.last_name)];
+    lines.extend(address.company.clone());
+    lines.push(address.address1.clone());
+    lines.extend(address.address2.clone().filter(|line| !line.is_empty()));
+    lines.push(
+        [Some(address.zip.as_str()), Some(address.city.as_str()), address.state.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    lines.push(address.country.to_uppercase());
+    lines
+}
+
+/// A label page: where the parcel goes and how
+fn label_lines(shipment: &PrintableShipment, layout: &PageLayout) -> Vec<String> {
+    let width = layout.line_width();
+    let mut lines = Vec::new();
+    if let Some(location) = &shipment.location_code {
+        lines.push(format!("FROM: {}", location));
+        lines.push(String::new());
+    }
+    lines.push("SHIP TO:".to_string());
+    lines.extend(address_lines(shipment).into_iter().map(|line| format!("  {}", line)));
+    lines.push(String::new());
+    let carrier = match (&shipment.carrier, &shipment.service_code) {
+        (Some(carrier), Some(service)) => format!("{} {}", carrier.to_uppercase(), service),
+        (Some(carrier), None) => carrier.to_uppercase(),
+        (None, Some(service)) => service.clone(),
+        (None, None) => "-".to_string(),
+    };
+    lines.push(format!("CARRIER:  {}", carrier));
+    lines.push(format!("TRACKING: {}", shipment.tracking_number.as_deref().unwrap_or("-")));
+    lines.push(format!("ORDER:    {}", shipment.order_number));
+    lines.push(String::new());
+    if let Some(url) = &shipment.label_url {
+        lines.push("Carrier label:".to_string());
+        lines.push(url.clone());
+    }
+    lines.into_iter().map(|line| truncate(&line, width)).collect()
+}
+
+/// A packing slip page: the order and the units in the parcel
+fn packing_slip_lines(shipment: &PrintableShipment, layout: &PageLayout) -> Vec<String> {
+    let width = layout.line_width();
+    let mut lines = vec![
+        format!("PACKING SLIP - Order {}", shipment.order_number),
+        format!("Shipment {}", shipment.shipment_id),
+        String::new(),
+        "Ship to:".to_string(),
+    ];
+    lines.extend(address_lines(shipment).into_iter().map(|line| format!("  {}", line)));
+    lines.push(String::new());
+    lines.push(format!("{:>5}  {}", "Qty", "Item"));
+    lines.push("-".repeat(width));
+    for line in &shipment.lines {
+        let title = match &line.variant_title {
+            Some(variant) if !variant.is_empty() => format!("{} - {}", line.title, variant),
+            _ => line.title.clone(),
+        };
+        lines.push(format!("{:>5}  {}", line.quantity, title));
+        if let Some(sku) = &line.sku {
+            lines.push(format!("{:>5}  SKU {}", "", sku));
+        }
+    }
+    lines.push("-".repeat(width));
+    lines.push(format!("{:>5}  units", shipment.lines.iter().map(|line| line.quantity).sum::<i32>()));
+    lines.into_iter().map(|line| truncate(&line, width)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PrintableLine;
+
+    fn shipment() -> PrintableShipment {
+        PrintableShipment {
+            shipment_id: Uuid::new_v4(),
+            order_number: "1001".to_string(),
+            location_code: Some("BER".to_string()),
+            carrier: Some("dhl".to_string()),
+            service_code: Some("P".to_string()),
+            tracking_number: Some("JD014600006101234567".to_string()),
+            label_url: Some("https://labels.example.com/a-very-long-path/to/the/carrier/label/document.pdf".to_string()),
+            ship_to: None,
+            lines: vec![
+                PrintableLine { title: "T-Shirt".to_string(), variant_title: Some("M".to_string()), sku: Some("TS-M".to_string()), quantity: 2 },
+                PrintableLine { title: "Mug".to_string(), variant_title: None, sku: None, quantity: 1 },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_label_fits_label_paper() {
+        let layout = page_layout(PaperSize::Label4x6);
+        let lines = label_lines(&shipment(), &layout);
+
+        assert!(lines.len() <= layout.lines_per_page());
+        assert!(lines.iter().all(|line| line.chars().count() <= layout.line_width()));
+        assert!(lines.contains(&"CARRIER:  DHL P".to_string()));
+        assert!(lines.contains(&"FROM: BER".to_string()));
+    }
+
+    #[test]
+    fn test_packing_slip_lists_units() {
+        let lines = packing_slip_lines(&shipment(), &page_layout(PaperSize::A4));
+
+        assert!(lines.contains(&"    2  T-Shirt - M".to_string()));
+        assert!(lines.contains(&"       SKU TS-M".to_string()));
+        assert_eq!(lines.last().unwrap(), "    3  units");
+    }
+
+    #[test]
+    fn test_batch_page_per_document() {
+        let layout = page_layout(PaperSize::Label4x6);
+        let documents: Vec<Vec<String>> = (0..3).map(|_| label_lines(&shipment(), &layout)).collect();
+        let (pdf, pages) = documents_pdf(&documents, &layout);
+
+        assert_eq!(pages, 3);
+        assert!(String::from_utf8_lossy(&pdf).contains("/MediaBox [0 0 288 432]"));
+    }
+}
@@ -1,11 +1,16 @@
 use std::sync::Arc;
+use rust_decimal::Decimal;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
     Result, Error,
     models::{
         Product, ProductVariant, ProductImage, ProductFilter,
         CreateProductRequest, UpdateProductRequest,
+        CreateVariantRequest, UpdateVariantRequest, ProductOptionSet,
+        ProductVariantWithOptions, VariantOption, Cursor, CursorPage,
+        AttributeFacet, AttributeWithOptions,
     },
     repository::ProductRepository,
     repository::traits::ProductRepositoryTrait,
@@ -61,24 +66,21 @@ impl ProductService {
         }))
     }
     
-    /// List products with filtering and pagination
+    /// List products with filtering and pagination, newest first or in
+    /// the sort order of the collection filtered by
     pub async fn list_products(
         &self,
         filter: Option<ProductFilter>,
         pagination: PaginationParams,
     ) -> Result<ProductList> {
         let filter = filter.unwrap_or_default();
-        let sort = crate::models::SortParams {
-            field: "created_at".to_string(),
-            direction: crate::models::SortDirection::Desc,
-        };
         
         let pagination = crate::models::Pagination {
             page: pagination.page,
             per_page: pagination.per_page,
         };
         
-        let products = self.repository.find_with_filter(&filter, &pagination, Some(&sort)).await?;
+        let products = self.repository.find_with_filter(&filter, &pagination, None).await?;
         let total = self.repository.count_by_filter(&filter).await?;
         
         Ok(ProductList {
@@ -92,6 +94,55 @@ impl ProductService {
         })
     }
     
+    /// A page of products newest first, continuing after `cursor`;
+    /// fetches one extra row to know whether there is a next page
+    pub async fn list_products_after(
+        &self,
+        filter: Option<ProductFilter>,
+        cursor: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<CursorPage<Product>> {
+        let filter = filter.unwrap_or_default();
+        let rows = self.repository.find_after(&filter, cursor, limit + 1).await?;
+        Ok(CursorPage::from_rows(rows, limit, |p| Cursor::new(p.created_at, p.id)))
+    }
+    
+    /// Facets of a listing for some filterable attributes
+    ///
+    /// Attributes the listing isn't filtered by are counted under the whole
+    /// filter, each one it is filtered by under the filter without its own
+    /// condition, so its other values show what picking them would add.
+    pub async fn attribute_facets(
+        &self,
+        filter: &ProductFilter,
+        attributes: &[AttributeWithOptions],
+    ) -> Result<Vec<AttributeFacet>> {
+        let selected: Vec<Uuid> = filter.attributes.iter().map(|c| c.attribute_id).collect();
+        let unselected: Vec<Uuid> = attributes
+            .iter()
+            .map(|a| a.attribute.id)
+            .filter(|id| !selected.contains(id))
+            .collect();
+        let mut counts = self.repository.attribute_facet_counts(filter, &unselected).await?;
+        for condition in &filter.attributes {
+            let mut others = filter.clone();
+            others.attributes.retain(|c| c.attribute_id != condition.attribute_id);
+            counts.extend(
+                self.repository
+                    .attribute_facet_counts(&others, &[condition.attribute_id])
+                    .await?,
+            );
+        }
+        
+        Ok(attributes
+            .iter()
+            .filter_map(|attribute| {
+                let condition = filter.attributes.iter().find(|c| c.attribute_id == attribute.attribute.id);
+                AttributeFacet::build(attribute, &counts, condition)
+            })
+            .collect())
+    }
+    
     /// Update product
     pub async fn update_product(&self, id: Uuid, request: UpdateProductRequest) -> Result<Product> {
         // Check if product exists
@@ -112,9 +163,7 @@ impl ProductService {
             .await?
             .ok_or_else(|| Error::not_found("Product not found"))?;
         
-        // Check if product has orders (soft delete in production)
-        // For MVP, we'll do hard delete
-        
+        // Soft delete: restorable until the purge job removes it
         self.repository.delete(id).await
     }
     
@@ -135,6 +184,190 @@ impl ProductService {
             images,
         }))
     }
+    
+    /// List a product's variants with their option values
+    pub async fn list_variants(&self, product_id: Uuid) -> Result<Vec<ProductVariantWithOptions>> {
+        self.require_product(product_id).await?;
+        
+        let variants = self.repository.find_variants(product_id).await?;
+        let mut options = self.repository.find_variant_options(product_id).await?;
+        
+        Ok(variants
+            .into_iter()
+            .map(|variant| {
+                let variant_options = options
+                    .iter()
+                    .filter(|(id, _)| *id == variant.id)
+                    .map(|(_, option)| option.clone())
+                    .collect();
+                options.retain(|(id, _)| *id != variant.id);
+                ProductVariantWithOptions { variant, options: variant_options }
+            })
+            .collect())
+    }
+    
+    /// Get a variant of a product with its option values
+    pub async fn get_variant(&self, product_id: Uuid, variant_id: Uuid) -> Result<Option<ProductVariantWithOptions>> {
+        Ok(self
+            .list_variants(product_id)
+            .await?
+            .into_iter()
+            .find(|v| v.variant.id == variant_id))
+    }
+    
+    /// List a product's options with the values in use
+    pub async fn list_options(&self, product_id: Uuid) -> Result<Vec<ProductOptionSet>> {
+        self.require_product(product_id).await?;
+        self.repository.find_options(product_id).await
+    }
+    
+    /// Add a variant to a product
+    pub async fn create_variant(&self, product_id: Uuid, request: CreateVariantRequest) -> Result<ProductVariantWithOptions> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.price < Decimal::ZERO {
+            return Err(Error::validation("Variant price cannot be negative"));
+        }
+        let product = self.require_product(product_id).await?;
+        
+        let existing = self.list_variants(product_id).await?;
+        check_variant_conflicts(&existing, None, request.sku.as_deref(), &request.options)?;
+        
+        let title = request.title.clone().unwrap_or_else(|| variant_title(&request.options));
+        let currency = request.currency.unwrap_or(product.currency);
+        let variant = self.repository.create_variant(product_id, &title, currency, &request).await?;
+        
+        Ok(ProductVariantWithOptions { variant, options: request.options })
+    }
+    
+    /// Update a variant; given options replace all of its option values
+    pub async fn update_variant(
+        &self,
+        product_id: Uuid,
+        variant_id: Uuid,
+        request: UpdateVariantRequest,
+    ) -> Result<ProductVariantWithOptions> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.price.is_some_and(|price| price < Decimal::ZERO) {
+            return Err(Error::validation("Variant price cannot be negative"));
+        }
+        
+        let existing = self.list_variants(product_id).await?;
+        let current = existing
+            .iter()
+            .find(|v| v.variant.id == variant_id)
+            .ok_or_else(|| Error::not_found("Variant not found"))?;
+        
+        let options = request.options.clone().unwrap_or_else(|| current.options.clone());
+        let sku = match request.sku {
+            Some(ref sku) => sku.clone(),
+            None => current.variant.sku.clone(),
+        };
+        check_variant_conflicts(&existing, Some(variant_id), sku.as_deref(), &options)?;
+        
+        let mut variant = current.variant.clone();
+        if let Some(title) = request.title {
+            variant.title = title;
+        } else if request.options.is_some() && variant.title == variant_title(&current.options) {
+            // Keep generated titles in step with the options
+            variant.title = variant_title(&options);
+        }
+        variant.sku = sku;
+        if let Some(barcode) = request.barcode {
+            variant.barcode = barcode;
+        }
+        if let Some(price) = request.price {
+            variant.price = price;
+        }
+        if let Some(compare_at_price) = request.compare_at_price {
+            variant.compare_at_price = compare_at_price;
+        }
+        if let Some(cost_price) = request.cost_price {
+            variant.cost_price = cost_price;
+        }
+        if let Some(inventory_quantity) = request.inventory_quantity {
+            variant.inventory_quantity = inventory_quantity;
+        }
+        if let Some(inventory_policy) = request.inventory_policy {
+            variant.inventory_policy = inventory_policy;
+        }
+        if let Some(weight) = request.weight {
+            variant.weight = weight;
+        }
+        if let Some(weight_unit) = request.weight_unit {
+            variant.weight_unit = weight_unit;
+        }
+        if let Some(requires_shipping) = request.requires_shipping {
+            variant.requires_shipping = requires_shipping;
+        }
+        if let Some(is_active) = request.is_active {
+            variant.is_active = is_active;
+        }
+        
+        let variant = self.repository.update_variant(&variant, request.options.as_deref()).await?;
+        
+        Ok(ProductVariantWithOptions { variant, options })
+    }
+    
+    /// Delete a variant
+    pub async fn delete_variant(&self, product_id: Uuid, variant_id: Uuid) -> Result<bool> {
+        self.require_product(product_id).await?;
+        self.repository.delete_variant(product_id, variant_id).await
+    }
+    
+    async fn require_product(&self, id: Uuid) -> Result<Product> {
+        self.repository.find_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Product not found"))
+    }
+}
+
+/// Title for a variant without one, e.g. "M / Red"
+fn variant_title(options: &[VariantOption]) -> String {
+    if options.is_empty() {
+        "Default".to_string()
+    } else {
+        options.iter().map(|o| o.value.as_str()).collect::<Vec<_>>().join(" / ")
+    }
+}
+
+/// Reject duplicate option names, SKUs already used by another variant of the
+/// product, and option combinations another variant already has
+fn check_variant_conflicts(
+    existing: &[ProductVariantWithOptions],
+    variant_id: Option<Uuid>,
+    sku: Option<&str>,
+    options: &[VariantOption],
+) -> Result<()> {
+    let mut names: Vec<String> = options.iter().map(|o| o.name.to_lowercase()).collect();
+    names.sort();
+    names.dedup();
+    if names.len() != options.len() {
+        return Err(Error::validation("Each option can only be given once per variant"));
+    }
+    
+    let key = option_key(options);
+    for other in existing.iter().filter(|v| Some(v.variant.id) != variant_id) {
+        if let (Some(sku), Some(other_sku)) = (sku, other.variant.sku.as_deref()) {
+            if sku.eq_ignore_ascii_case(other_sku) {
+                return Err(Error::validation(format!("SKU '{}' is already used by variant '{}'", sku, other.variant.title)));
+            }
+        }
+        if !options.is_empty() && option_key(&other.options) == key {
+            return Err(Error::validation(format!("Variant '{}' already has these options", other.variant.title)));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Order-independent, case-insensitive key for an option combination
+fn option_key(options: &[VariantOption]) -> Vec<(String, String)> {
+    let mut key: Vec<(String, String)> = options
+        .iter()
+        .map(|o| (o.name.to_lowercase(), o.value.to_lowercase()))
+        .collect();
+    key.sort();
+    key
 }
 
 #[async_trait::async_trait]
@@ -159,4 +392,62 @@ pub struct ProductDetail {
 pub struct ProductList {
     pub products: Vec<Product>,
     pub pagination: crate::services::PaginationInfo,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, InventoryPolicy};
+    use chrono::Utc;
+
+    fn option(name: &str, value: &str) -> VariantOption {
+        VariantOption { name: name.to_string(), value: value.to_string() }
+    }
+
+    fn variant(sku: &str, options: Vec<VariantOption>) -> ProductVariantWithOptions {
+        ProductVariantWithOptions {
+            variant: ProductVariant {
+                id: Uuid::new_v4(),
+                product_id: Uuid::new_v4(),
+                title: variant_title(&options),
+                sku: Some(sku.to_string()),
+                price: Decimal::new(1999, 2),
+                compare_at_price: None,
+                cost_price: None,
+                currency: Currency::USD,
+                inventory_quantity: 10,
+                inventory_policy: InventoryPolicy::Deny,
+                weight: None,
+                weight_unit: None,
+                requires_shipping: true,
+                is_active: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                barcode: None,
+            },
+            options,
+        }
+    }
+
+    #[test]
+    fn test_variant_title() {
+        assert_eq!(variant_title(&[option("Size", "M"), option("Color", "Red")]), "M / Red");
+        assert_eq!(variant_title(&[]), "Default");
+    }
+
+    #[test]
+    fn test_check_variant_conflicts() {
+        let existing = vec![variant("TEE-M-RED", vec![option("Size", "M"), option("Color", "Red")])];
+
+        // Same combination in a different order and case
+        assert!(check_variant_conflicts(&existing, None, None, &[option("color", "red"), option("size", "m")]).is_err());
+        // SKU already taken
+        assert!(check_variant_conflicts(&existing, None, Some("tee-m-red"), &[option("Size", "L")]).is_err());
+        // Same option twice
+        assert!(check_variant_conflicts(&existing, None, None, &[option("Size", "L"), option("size", "XL")]).is_err());
+        // New combination
+        assert!(check_variant_conflicts(&existing, None, Some("TEE-L-RED"), &[option("Size", "L"), option("Color", "Red")]).is_ok());
+        // A variant doesn't conflict with itself
+        let id = existing[0].variant.id;
+        assert!(check_variant_conflicts(&existing, Some(id), Some("TEE-M-RED"), &existing[0].options).is_ok());
+    }
+}
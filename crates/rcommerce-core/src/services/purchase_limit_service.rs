@@ -0,0 +1,395 @@
+//! Purchase Limit Service
+//!
+//! Admin management of purchase limit rules and allocation lists, and the
+//! `PurchaseLimiter` that enforces them. The cart service checks a cart's
+//! items as they are added; checkout checks them again with the shipping
+//! address, so household rules apply, and records the household on the
+//! order for later checks.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    AddAllocationMembersRequest, AllocationList, AllocationListDetails, AllocationListMember, Buyer,
+    CreateAllocationListRequest, CreatePurchaseLimitRuleRequest, PurchaseLimitRule, PurchaseLine,
+    UpdatePurchaseLimitRuleRequest,
+};
+use crate::repository::PurchaseLimitRepository;
+use crate::{Error, Result};
+
+/// Enforces purchase limit rules and allocation lists
+#[derive(Clone)]
+pub struct PurchaseLimiter {
+    repository: Arc<dyn PurchaseLimitRepository>,
+}
+
+impl PurchaseLimiter {
+    pub fn new(repository: Arc<dyn PurchaseLimitRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Refuse `lines` if `buyer` may not buy them at `now`, with the first
+    /// `PurchaseLimitViolation` as a coded validation error
+    pub async fn check(&self, buyer: &Buyer, lines: &[PurchaseLine], now: DateTime<Utc>) -> Result<()> {
+        let product_ids: Vec<Uuid> = lines
+            .iter()
+            .filter(|line| line.quantity > 0)
+            .map(|line| line.product_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if product_ids.is_empty() {
+            return Ok(());
+        }
+
+        // Early access first: there is no point counting purchases of something the buyer cannot buy
+        let lists = self.repository.restricted_lists(&product_ids, now).await?;
+        let lists: Vec<AllocationList> = lists
+            .into_iter()
+            .filter(|list| lines.iter().any(|line| line.quantity > 0 && list.covers(line.product_id, line.variant_id)))
+            .collect();
+        if !lists.is_empty() {
+            let member_of = match buyer.customer_id {
+                Some(customer_id) => {
+                    let ids: Vec<Uuid> = lists.iter().map(|list| list.id).collect();
+                    self.repository.member_of(customer_id, &ids).await?
+                }
+                None => Vec::new(),
+            };
+            if let Some(violation) = lists.iter().find_map(|list| list.violation(member_of.contains(&list.id), now)) {
+                return Err(violation.into_error());
+            }
+        }
+
+        for rule in self.repository.active_rules(&product_ids).await? {
+            let wanted = rule.wanted(lines);
+            if wanted <= 0 {
+                continue;
+            }
+            let identified = buyer.customer_id.is_some()
+                || buyer.email.is_some()
+                || (rule.per_household && buyer.household_key.is_some());
+            let bought = if identified {
+                let since = rule.window_hours.map(|hours| now - Duration::hours(i64::from(hours)));
+                self.repository.purchased(&rule, buyer, since).await?
+            } else {
+                0
+            };
+            if let Some(violation) = rule.violation(wanted, bought) {
+                return Err(violation.into_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Remember the household an order shipped to, for household rules
+    pub async fn record_household(&self, order_id: Uuid, household_key: &str) -> Result<()> {
+        self.repository.set_household(order_id, household_key).await
+    }
+}
+
+/// Purchase limit service
+pub struct PurchaseLimitService<R: PurchaseLimitRepository> {
+    repository: R,
+}
+
+impl<R: PurchaseLimitRepository> PurchaseLimitService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Define a rule
+    pub async fn create_rule(&self, request: CreatePurchaseLimitRuleRequest, created_by: Option<Uuid>) -> Result<PurchaseLimitRule> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let rule = self.repository.create_rule(&request, created_by).await?;
+        tracing::info!("Created purchase limit rule {} ({} per {})", rule.id, rule.max_quantity,
+            if rule.per_household { "household" } else { "customer" });
+        Ok(rule)
+    }
+
+    /// Get a rule
+    pub async fn get_rule(&self, id: Uuid) -> Result<PurchaseLimitRule> {
+        self.repository
+            .find_rule(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Purchase limit rule not found"))
+    }
+
+    /// Rules, optionally for one product
+    pub async fn list_rules(&self, product_id: Option<Uuid>) -> Result<Vec<PurchaseLimitRule>> {
+        self.repository.list_rules(product_id).await
+    }
+
+    /// Rename, change the limit of, or switch a rule on or off
+    pub async fn update_rule(&self, id: Uuid, request: UpdatePurchaseLimitRuleRequest) -> Result<PurchaseLimitRule> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        self.repository
+            .update_rule(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Purchase limit rule not found"))
+    }
+
+    /// Delete a rule
+    pub async fn delete_rule(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete_rule(id).await? {
+            return Err(Error::not_found("Purchase limit rule not found"));
+        }
+        Ok(())
+    }
+
+    /// Define an allocation list; it opens to members now unless `opens_at` is given
+    pub async fn create_list(&self, request: CreateAllocationListRequest, created_by: Option<Uuid>) -> Result<AllocationList> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        let opens_at = request.opens_at.unwrap_or_else(Utc::now);
+        if request.public_at.is_some_and(|public_at| public_at <= opens_at) {
+            return Err(Error::validation("Allocation list must go public after it opens"));
+        }
+        let list = self.repository.create_list(&request, opens_at, created_by).await?;
+        tracing::info!("Created allocation list {} for product {}", list.id, list.product_id);
+        Ok(list)
+    }
+
+    /// Get a list with its member count
+    pub async fn get_list(&self, id: Uuid) -> Result<AllocationListDetails> {
+        let list = self
+            .repository
+            .find_list(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Allocation list not found"))?;
+        let member_count = self.repository.member_count(id).await?;
+        Ok(AllocationListDetails { list, member_count })
+    }
+
+    /// Lists, optionally for one product
+    pub async fn list_lists(&self, product_id: Option<Uuid>) -> Result<Vec<AllocationList>> {
+        self.repository.list_lists(product_id).await
+    }
+
+    /// Delete a list and its members
+    pub async fn delete_list(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete_list(id).await? {
+            return Err(Error::not_found("Allocation list not found"));
+        }
+        Ok(())
+    }
+
+    /// Add customers by id or email; unknown ones are skipped. Returns how many were added.
+    pub async fn add_members(&self, list_id: Uuid, request: AddAllocationMembersRequest) -> Result<u64> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.customer_ids.is_empty() && request.emails.is_empty() {
+            return Err(Error::validation("Give customer_ids or emails to add"));
+        }
+        self.get_list(list_id).await?;
+        self.repository.add_members(list_id, &request.customer_ids, &request.emails).await
+    }
+
+    /// Take a customer off a list
+    pub async fn remove_member(&self, list_id: Uuid, customer_id: Uuid) -> Result<()> {
+        if !self.repository.remove_member(list_id, customer_id).await? {
+            return Err(Error::not_found("Customer is not on this allocation list"));
+        }
+        Ok(())
+    }
+
+    /// A page of members
+    pub async fn members(&self, list_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AllocationListMember>> {
+        self.get_list(list_id).await?;
+        self.repository.members(list_id, limit, offset).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Rules and lists in memory; `purchased` returns a fixed count and records its window
+    #[derive(Default)]
+    struct MockRepository {
+        rules: Vec<PurchaseLimitRule>,
+        lists: Vec<AllocationList>,
+        members: Vec<(Uuid, Uuid)>,
+        bought: i64,
+        since: Mutex<Vec<Option<DateTime<Utc>>>>,
+    }
+
+    #[async_trait]
+    impl PurchaseLimitRepository for MockRepository {
+        async fn create_rule(&self, _: &CreatePurchaseLimitRuleRequest, _: Option<Uuid>) -> Result<PurchaseLimitRule> {
+            unimplemented!()
+        }
+        async fn find_rule(&self, _: Uuid) -> Result<Option<PurchaseLimitRule>> {
+            unimplemented!()
+        }
+        async fn list_rules(&self, _: Option<Uuid>) -> Result<Vec<PurchaseLimitRule>> {
+            unimplemented!()
+        }
+        async fn update_rule(&self, _: Uuid, _: &UpdatePurchaseLimitRuleRequest) -> Result<Option<PurchaseLimitRule>> {
+            unimplemented!()
+        }
+        async fn delete_rule(&self, _: Uuid) -> Result<bool> {
+            unimplemented!()
+        }
+        async fn active_rules(&self, product_ids: &[Uuid]) -> Result<Vec<PurchaseLimitRule>> {
+            Ok(self.rules.iter().filter(|rule| product_ids.contains(&rule.product_id)).cloned().collect())
+        }
+        async fn purchased(&self, _: &PurchaseLimitRule, _: &Buyer, since: Option<DateTime<Utc>>) -> Result<i64> {
+            self.since.lock().unwrap().push(since);
+            Ok(self.bought)
+        }
+        async fn set_household(&self, _: Uuid, _: &str) -> Result<()> {
+            unimplemented!()
+        }
+        async fn create_list(&self, _: &CreateAllocationListRequest, _: DateTime<Utc>, _: Option<Uuid>) -> Result<AllocationList> {
+            unimplemented!()
+        }
+        async fn find_list(&self, _: Uuid) -> Result<Option<AllocationList>> {
+            unimplemented!()
+        }
+        async fn list_lists(&self, _: Option<Uuid>) -> Result<Vec<AllocationList>> {
+            unimplemented!()
+        }
+        async fn delete_list(&self, _: Uuid) -> Result<bool> {
+            unimplemented!()
+        }
+        async fn restricted_lists(&self, product_ids: &[Uuid], now: DateTime<Utc>) -> Result<Vec<AllocationList>> {
+            Ok(self
+                .lists
+                .iter()
+                .filter(|list| product_ids.contains(&list.product_id) && list.public_at.map_or(true, |at| at > now))
+                .cloned()
+                .collect())
+        }
+        async fn member_of(&self, customer_id: Uuid, list_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+            Ok(self
+                .members
+                .iter()
+                .filter(|(list_id, member)| *member == customer_id && list_ids.contains(list_id))
+                .map(|(list_id, _)| *list_id)
+                .collect())
+        }
+        async fn add_members(&self, _: Uuid, _: &[Uuid], _: &[String]) -> Result<u64> {
+            unimplemented!()
+        }
+        async fn remove_member(&self, _: Uuid, _: Uuid) -> Result<bool> {
+            unimplemented!()
+        }
+        async fn members(&self, _: Uuid, _: i64, _: i64) -> Result<Vec<AllocationListMember>> {
+            unimplemented!()
+        }
+        async fn member_count(&self, _: Uuid) -> Result<i64> {
+            unimplemented!()
+        }
+    }
+
+    fn rule(product_id: Uuid, max_quantity: i32, window_hours: Option<i32>, per_household: bool) -> PurchaseLimitRule {
+        PurchaseLimitRule {
+            id: Uuid::new_v4(),
+            name: "Limit".to_string(),
+            product_id,
+            variant_id: None,
+            max_quantity,
+            window_hours,
+            per_household,
+            is_active: true,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn line(product_id: Uuid, variant_id: Option<Uuid>, quantity: i64) -> PurchaseLine {
+        PurchaseLine { product_id, variant_id, quantity }
+    }
+
+    fn code(error: Error) -> String {
+        let Error::Validation(body) = error else {
+            panic!("expected a validation error, got {}", error);
+        };
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        body["errors"][0]["code"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_limit_counts_variants_and_past_orders() {
+        let product_id = Uuid::new_v4();
+        let repository = Arc::new(MockRepository {
+            rules: vec![rule(product_id, 3, Some(24), false)],
+            bought: 1,
+            ..Default::default()
+        });
+        let limiter = PurchaseLimiter::new(repository.clone());
+        let buyer = Buyer { customer_id: Some(Uuid::new_v4()), ..Default::default() };
+        let now = Utc::now();
+
+        let two = [line(product_id, Some(Uuid::new_v4()), 1), line(product_id, Some(Uuid::new_v4()), 1)];
+        assert!(limiter.check(&buyer, &two, now).await.is_ok());
+        assert_eq!(repository.since.lock().unwrap()[0], Some(now - Duration::hours(24)));
+
+        let three = [line(product_id, None, 3)];
+        assert_eq!(code(limiter.check(&buyer, &three, now).await.unwrap_err()), "purchase_limit_exceeded");
+
+        // Other products are not limited
+        assert!(limiter.check(&buyer, &[line(Uuid::new_v4(), None, 10)], now).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_household_limit() {
+        let product_id = Uuid::new_v4();
+        let limiter = PurchaseLimiter::new(Arc::new(MockRepository {
+            rules: vec![rule(product_id, 1, None, true)],
+            bought: 1,
+            ..Default::default()
+        }));
+        let household = Buyer { household_key: Some("key".to_string()), ..Default::default() };
+        let error = limiter.check(&household, &[line(product_id, None, 1)], Utc::now()).await.unwrap_err();
+        assert_eq!(code(error), "household_limit_exceeded");
+
+        // An anonymous cart cannot be matched to past orders yet
+        assert!(limiter.check(&Buyer::default(), &[line(product_id, None, 1)], Utc::now()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_allocation_list_early_access() {
+        let product_id = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        let now = Utc::now();
+        let list = AllocationList {
+            id: Uuid::new_v4(),
+            name: "VIP".to_string(),
+            product_id,
+            variant_id: None,
+            opens_at: now - Duration::hours(1),
+            public_at: Some(now + Duration::hours(1)),
+            is_active: true,
+            created_by: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let limiter = PurchaseLimiter::new(Arc::new(MockRepository {
+            members: vec![(list.id, member)],
+            lists: vec![list],
+            ..Default::default()
+        }));
+        let lines = [line(product_id, None, 1)];
+
+        let member = Buyer { customer_id: Some(member), ..Default::default() };
+        assert!(limiter.check(&member, &lines, now).await.is_ok());
+        let error = limiter.check(&member, &lines, now - Duration::hours(2)).await.unwrap_err();
+        assert_eq!(code(error), "not_yet_available");
+
+        let stranger = Buyer { customer_id: Some(Uuid::new_v4()), ..Default::default() };
+        assert_eq!(code(limiter.check(&stranger, &lines, now).await.unwrap_err()), "early_access_only");
+        assert_eq!(code(limiter.check(&Buyer::default(), &lines, now).await.unwrap_err()), "early_access_only");
+        assert!(limiter.check(&stranger, &lines, now + Duration::hours(2)).await.is_ok());
+    }
+}
@@ -0,0 +1,630 @@
+//! Redirect Service
+//!
+//! Resolves storefront paths that moved: manual redirects first (an exact
+//! source, else the longest matching `*` prefix), then product and category
+//! URLs (`[redirects]` paths) whose slug is one the entity used before,
+//! which redirect permanently to its current slug.
+//!
+//! Manual redirects are served from an in-memory table reloaded every
+//! `cache_ttl_secs` and whenever they change through this service; hits are
+//! counted in memory and written back when the table reloads.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::config::RedirectsConfig;
+use crate::models::{
+    CreateUrlRedirectRequest, RedirectImportError, RedirectImportResult, RedirectResolution, RedirectSource,
+    SlugEntityType, SlugHistoryEntry, SlugLookup, UpdateUrlRedirectRequest, UrlRedirect,
+};
+use crate::repository::RedirectRepository;
+use crate::{Error, Result};
+
+/// Status codes a manual redirect can use
+const REDIRECT_STATUS_CODES: [i16; 4] = [301, 302, 307, 308];
+
+/// Rows accepted in one CSV import
+const MAX_IMPORT_ROWS: usize = 50_000;
+
+/// Column names other platforms export redirects with (lowercase, `_` for
+/// spaces and dashes)
+const SOURCE_COLUMNS: &[&str] = &[
+    "source", "source_path", "source_url", "from", "redirect_from", "old_url", "old_path", "request_path", "url",
+];
+const TARGET_COLUMNS: &[&str] = &[
+    "target", "target_path", "target_url", "to", "redirect_to", "new_url", "new_path", "destination",
+];
+const STATUS_COLUMNS: &[&str] = &[
+    "status", "status_code", "code", "action_code", "http_code", "type", "redirect_type",
+];
+
+/// A manual redirect as served from memory
+#[derive(Debug, Clone)]
+struct CachedRedirect {
+    id: Uuid,
+    target_path: String,
+    status_code: i16,
+}
+
+/// Manual redirects by exact source, and `*` sources by prefix, longest first
+#[derive(Debug, Default)]
+struct RedirectTable {
+    exact: HashMap<String, CachedRedirect>,
+    wildcards: Vec<(String, CachedRedirect)>,
+}
+
+impl RedirectTable {
+    fn new(redirects: Vec<UrlRedirect>) -> Self {
+        let mut table = Self::default();
+        for redirect in redirects {
+            let cached = CachedRedirect {
+                id: redirect.id,
+                target_path: redirect.target_path,
+                status_code: redirect.status_code,
+            };
+            match redirect.source_path.strip_suffix('*') {
+                Some(prefix) => table.wildcards.push((prefix.to_string(), cached)),
+                None => {
+                    table.exact.insert(redirect.source_path, cached);
+                }
+            }
+        }
+        table.wildcards.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        table
+    }
+
+    /// The redirect a path matches and the location it goes to
+    fn lookup(&self, path: &str) -> Option<(&CachedRedirect, String)> {
+        if let Some(redirect) = self.exact.get(path) {
+            return Some((redirect, redirect.target_path.clone()));
+        }
+        self.wildcards.iter().find_map(|(prefix, redirect)| {
+            let rest = match_wildcard(prefix, path)?;
+            Some((redirect, redirect.target_path.replacen('*', rest, 1)))
+        })
+    }
+}
+
+/// Redirect service
+pub struct RedirectService<R: RedirectRepository> {
+    repository: R,
+    config: RedirectsConfig,
+    table: RwLock<Option<(Arc<RedirectTable>, Instant)>>,
+    hits: Mutex<HashMap<Uuid, i64>>,
+}
+
+impl<R: RedirectRepository> RedirectService<R> {
+    pub fn new(repository: R, config: RedirectsConfig) -> Self {
+        Self {
+            repository,
+            config,
+            table: RwLock::new(None),
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Where a storefront path redirects to; None when it hasn't moved
+    pub async fn resolve(&self, path: &str) -> Result<Option<RedirectResolution>> {
+        let path = normalize_path(path)?;
+
+        let table = self.table().await?;
+        if let Some((redirect, location)) = table.lookup(&path) {
+            *self.hits.lock().await.entry(redirect.id).or_default() += 1;
+            return Ok(Some(RedirectResolution {
+                location,
+                status_code: redirect.status_code as u16,
+                source: RedirectSource::Manual,
+                entity_type: None,
+                entity_id: None,
+            }));
+        }
+
+        for (entity_type, pattern) in [
+            (SlugEntityType::Product, &self.config.product_path),
+            (SlugEntityType::Category, &self.config.category_path),
+        ] {
+            let Some(slug) = match_slug(pattern, &path) else {
+                continue;
+            };
+            if let Some(lookup) = self.repository.lookup_slug(entity_type, slug).await? {
+                if lookup.slug != slug {
+                    return Ok(Some(RedirectResolution {
+                        location: pattern.replace("{slug}", &lookup.slug),
+                        status_code: 301,
+                        source: RedirectSource::SlugHistory,
+                        entity_type: Some(entity_type),
+                        entity_id: Some(lookup.entity_id),
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The product or category using a slug now, or else the one that used
+    /// it last
+    pub async fn lookup_slug(&self, entity_type: SlugEntityType, slug: &str) -> Result<Option<SlugLookup>> {
+        self.repository.lookup_slug(entity_type, slug).await
+    }
+
+    /// An entity's old slugs, newest first
+    pub async fn slug_history(&self, entity_type: SlugEntityType, entity_id: Uuid) -> Result<Vec<SlugHistoryEntry>> {
+        self.repository.slug_history(entity_type, entity_id).await
+    }
+
+    pub async fn list(&self, limit: i64, offset: i64) -> Result<Vec<UrlRedirect>> {
+        self.repository.list(limit, offset).await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<UrlRedirect> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Redirect not found"))
+    }
+
+    pub async fn create(&self, request: CreateUrlRedirectRequest, created_by: Option<Uuid>) -> Result<UrlRedirect> {
+        let source_path = normalize_source(&request.source_path)?;
+        let target_path = normalize_target(&request.target_path)?;
+        let status_code = check_status_code(request.status_code.unwrap_or(301))?;
+        self.check_loop(&source_path, &target_path).await?;
+        let redirect = self
+            .repository
+            .create(&source_path, &target_path, status_code, created_by)
+            .await?;
+        self.invalidate().await;
+        Ok(redirect)
+    }
+
+    pub async fn update(&self, id: Uuid, request: UpdateUrlRedirectRequest) -> Result<UrlRedirect> {
+        let redirect = self.get(id).await?;
+        let target_path = match request.target_path {
+            Some(ref target) => normalize_target(target)?,
+            None => redirect.target_path,
+        };
+        let status_code = check_status_code(request.status_code.unwrap_or(redirect.status_code))?;
+        self.check_loop(&redirect.source_path, &target_path).await?;
+        let redirect = self
+            .repository
+            .update(id, &target_path, status_code)
+            .await?
+            .ok_or_else(|| Error::not_found("Redirect not found"))?;
+        self.invalidate().await;
+        Ok(redirect)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        if !self.repository.delete(id).await? {
+            return Err(Error::not_found("Redirect not found"));
+        }
+        self.invalidate().await;
+        Ok(())
+    }
+
+    /// Import redirects from a CSV export (see `parse_redirect_csv`); rows
+    /// that don't validate are reported and the rest imported together.
+    /// Existing sources are replaced with `overwrite` and skipped otherwise.
+    pub async fn import_csv(
+        &self,
+        data: &str,
+        overwrite: bool,
+        created_by: Option<Uuid>,
+    ) -> Result<RedirectImportResult> {
+        let (rows, mut errors) = parse_redirect_csv(data)?;
+        if rows.len() > MAX_IMPORT_ROWS {
+            return Err(Error::validation(format!(
+                "A redirect import can have at most {} rows",
+                MAX_IMPORT_ROWS
+            )));
+        }
+
+        let mut redirects: Vec<CreateUrlRedirectRequest> = Vec::new();
+        let mut lines: HashMap<String, usize> = HashMap::new();
+        for (line, row) in rows {
+            let redirect = normalize_source(&row.source_path).and_then(|source_path| {
+                let target_path = normalize_target(&row.target_path)?;
+                let status_code = check_status_code(row.status_code.unwrap_or(301))?;
+                check_redirect(&source_path, &target_path)?;
+                Ok(CreateUrlRedirectRequest {
+                    source_path,
+                    target_path,
+                    status_code: Some(status_code),
+                })
+            });
+            match redirect {
+                Ok(redirect) => {
+                    if let Some(first) = lines.get(&redirect.source_path) {
+                        errors.push(RedirectImportError {
+                            line,
+                            message: format!("{} is already redirected on line {}", redirect.source_path, first),
+                        });
+                        continue;
+                    }
+                    lines.insert(redirect.source_path.clone(), line);
+                    redirects.push(redirect);
+                }
+                Err(e) => errors.push(RedirectImportError { line, message: e.to_string() }),
+            }
+        }
+
+        // Two redirects pointing at each other, in the file or with one
+        // already saved
+        let table = self.table().await?;
+        let targets: HashMap<&str, &str> = redirects
+            .iter()
+            .map(|r| (r.source_path.as_str(), r.target_path.as_str()))
+            .collect();
+        let (redirects, loops): (Vec<_>, Vec<_>) = redirects.iter().partition(|r| {
+            let back = targets
+                .get(r.target_path.as_str())
+                .copied()
+                .or_else(|| table.exact.get(&r.target_path).map(|next| next.target_path.as_str()));
+            back != Some(r.source_path.as_str())
+        });
+        for redirect in loops {
+            errors.push(RedirectImportError {
+                line: lines[&redirect.source_path],
+                message: format!("{} redirects back to {}", redirect.target_path, redirect.source_path),
+            });
+        }
+        errors.sort_by_key(|e| e.line);
+
+        let redirects: Vec<CreateUrlRedirectRequest> = redirects.into_iter().cloned().collect();
+        let (created, updated) = if redirects.is_empty() {
+            (0, 0)
+        } else {
+            self.repository.import(&redirects, overwrite, created_by).await?
+        };
+        self.invalidate().await;
+        Ok(RedirectImportResult {
+            created,
+            updated,
+            skipped: redirects.len() as u64 - created - updated,
+            errors,
+        })
+    }
+
+    /// Manual redirects, reloaded (after saving counted hits) once older
+    /// than `cache_ttl_secs`
+    async fn table(&self) -> Result<Arc<RedirectTable>> {
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        if let Some((table, loaded_at)) = self.table.read().await.as_ref() {
+            if loaded_at.elapsed() < ttl {
+                return Ok(table.clone());
+            }
+        }
+
+        let mut cached = self.table.write().await;
+        if let Some((table, loaded_at)) = cached.as_ref() {
+            if loaded_at.elapsed() < ttl {
+                return Ok(table.clone());
+            }
+        }
+        self.flush_hits().await;
+        let table = Arc::new(RedirectTable::new(self.repository.all().await?));
+        *cached = Some((table.clone(), Instant::now()));
+        Ok(table)
+    }
+
+    /// Reload manual redirects on the next lookup
+    async fn invalidate(&self) {
+        *self.table.write().await = None;
+    }
+
+    /// Write hits counted since the last reload
+    async fn flush_hits(&self) {
+        let hits: Vec<(Uuid, i64)> = std::mem::take(&mut *self.hits.lock().await).into_iter().collect();
+        if hits.is_empty() {
+            return;
+        }
+        if let Err(e) = self.repository.record_hits(&hits).await {
+            tracing::warn!("Failed to record hits of {} redirects: {}", hits.len(), e);
+        }
+    }
+
+    /// A redirect must not point at itself, or at a redirect back to it
+    async fn check_loop(&self, source_path: &str, target_path: &str) -> Result<()> {
+        check_redirect(source_path, target_path)?;
+        if target_path.starts_with('/') {
+            if let Some(next) = self.repository.find_by_source(target_path).await? {
+                if next.target_path == source_path {
+                    return Err(Error::validation(format!(
+                        "{} already redirects to {}",
+                        target_path, source_path
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A storefront path without its query, fragment or trailing slash
+fn normalize_path(path: &str) -> Result<String> {
+    let path = path.trim();
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    if !path.starts_with('/') || path.starts_with("//") || path.chars().any(char::is_whitespace) {
+        return Err(Error::validation("Paths must start with a single / and contain no spaces"));
+    }
+    let trimmed = path.trim_end_matches('/');
+    Ok(if trimmed.is_empty() { "/".to_string() } else { trimmed.to_string() })
+}
+
+/// A redirect source: a path, or a path prefix ending in `*`
+fn normalize_source(source: &str) -> Result<String> {
+    let source = normalize_path(source)?;
+    if source.trim_end_matches('*').contains('*') || source.ends_with("**") {
+        return Err(Error::validation("source_path can only end in a single *"));
+    }
+    Ok(source)
+}
+
+/// A redirect target: a storefront path (kept with its query) or an
+/// absolute http(s) URL
+fn normalize_target(target: &str) -> Result<String> {
+    let target = target.trim();
+    if target.starts_with('/') {
+        if target.starts_with("//") || target.chars().any(char::is_whitespace) {
+            return Err(Error::validation("Paths must start with a single / and contain no spaces"));
+        }
+        return Ok(target.to_string());
+    }
+    url::Url::parse(target)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "https" | "http"))
+        .map(|url| url.to_string())
+        .ok_or_else(|| Error::validation("target_path must be a path or an http(s) URL"))
+}
+
+/// Checks of a redirect's source and target that need no lookups: a `*`
+/// in the target needs a `*` source, and a redirect can't match its own
+/// target
+fn check_redirect(source_path: &str, target_path: &str) -> Result<()> {
+    if source_path == target_path {
+        return Err(Error::validation("A redirect cannot point at its own path"));
+    }
+    match source_path.strip_suffix('*') {
+        Some(prefix) => {
+            if target_path.matches('*').count() > 1 {
+                return Err(Error::validation("target_path can contain at most one *"));
+            }
+            let path = target_path.replacen('*', "", 1);
+            let path = path.split(['?', '#']).next().unwrap_or_default();
+            if match_wildcard(prefix, path).is_some() {
+                return Err(Error::validation(format!("{} would redirect to itself", source_path)));
+            }
+        }
+        None if target_path.contains('*') => {
+            return Err(Error::validation("A * in target_path needs a source_path ending in *"));
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+fn check_status_code(status_code: i16) -> Result<i16> {
+    if !REDIRECT_STATUS_CODES.contains(&status_code) {
+        return Err(Error::validation("status_code must be 301, 302, 307 or 308"));
+    }
+    Ok(status_code)
+}
+
+/// The slug in a path matching a pattern like `/products/{slug}`
+fn match_slug<'a>(pattern: &str, path: &'a str) -> Option<&'a str> {
+    let (prefix, suffix) = pattern.split_once("{slug}")?;
+    let slug = path.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    (!slug.is_empty() && !slug.contains('/')).then_some(slug)
+}
+
+/// The rest of a path under a `*` source's prefix; `/blog/*` matches
+/// `/blog` itself too
+fn match_wildcard<'a>(prefix: &str, path: &'a str) -> Option<&'a str> {
+    path.strip_prefix(prefix)
+        .or_else(|| (prefix.len() > 1 && path == prefix.trim_end_matches('/')).then_some(""))
+}
+
+/// A redirect source from a CSV row: full URLs keep only their path, and
+/// relative paths (as Magento exports them) get a leading /
+fn csv_source(value: &str) -> String {
+    let value = value.trim();
+    match url::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "https" | "http") => url.path().to_string(),
+        _ if value.starts_with('/') => value.to_string(),
+        _ => format!("/{}", value),
+    }
+}
+
+/// A redirect read from a CSV export, with its line number
+type CsvRedirectRow = (usize, CreateUrlRedirectRequest);
+
+/// Redirects in a CSV export, with their line numbers, and the rows that
+/// couldn't be read. The header names the columns: the source (`source`,
+/// `from`, `Redirect from`, `request_path`, ...), the target (`target`,
+/// `to`, `Redirect to`, `target_path`, ...) and optionally the status code
+/// (`status`, `code`, `redirect_type`, ...; 301 when empty).
+fn parse_redirect_csv(data: &str) -> Result<(Vec<CsvRedirectRow>, Vec<RedirectImportError>)> {
+    let data = data.trim_start_matches('\u{feff}');
+    // Line from the byte offset: the reader's line count, and the offset
+    // itself, start at blank lines before a record
+    let line_at = |position: Option<&csv::Position>| {
+        position.map_or(0, |p| {
+            let (before, rest) = data.split_at(p.byte() as usize);
+            let blank = rest.len() - rest.trim_start_matches(['\r', '\n']).len();
+            before.matches('\n').count() + rest[..blank].matches('\n').count() + 1
+        })
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| Error::validation(format!("Invalid CSV header: {}", e)))?
+        .iter()
+        .map(|h| h.to_lowercase().replace([' ', '-'], "_"))
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let (Some(source), Some(target)) = (column(SOURCE_COLUMNS), column(TARGET_COLUMNS)) else {
+        return Err(Error::validation("The CSV header needs a source and a target column"));
+    };
+    let status = column(STATUS_COLUMNS);
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = line_at(e.position());
+                errors.push(RedirectImportError { line, message: format!("Invalid CSV row: {}", e) });
+                continue;
+            }
+        };
+        let line = line_at(record.position());
+        let field = |index: usize| record.get(index).unwrap_or_default();
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let status_code = match status.map(field).filter(|s| !s.is_empty()) {
+            Some(code) => match code.parse::<i16>() {
+                Ok(code) => Some(code),
+                Err(_) => {
+                    errors.push(RedirectImportError { line, message: format!("Invalid status code {}", code) });
+                    continue;
+                }
+            },
+            None => None,
+        };
+        rows.push((
+            line,
+            CreateUrlRedirectRequest {
+                source_path: csv_source(field(source)),
+                target_path: field(target).to_string(),
+                status_code,
+            },
+        ));
+    }
+    Ok((rows, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/products/runner/").unwrap(), "/products/runner");
+        assert_eq!(normalize_path(" /products/runner?utm_source=x#reviews ").unwrap(), "/products/runner");
+        assert_eq!(normalize_path("/").unwrap(), "/");
+        assert!(normalize_path("products/runner").is_err());
+        assert!(normalize_path("//evil.example.com/").is_err());
+        assert!(normalize_path("https://shop.example.com/products/runner").is_err());
+    }
+
+    #[test]
+    fn test_normalize_target() {
+        assert_eq!(normalize_target("/sale?season=summer").unwrap(), "/sale?season=summer");
+        assert_eq!(normalize_target("https://blog.example.com/post").unwrap(), "https://blog.example.com/post");
+        assert!(normalize_target("//evil.example.com/").is_err());
+        assert!(normalize_target("javascript:alert(1)").is_err());
+        assert!(normalize_target("sale").is_err());
+    }
+
+    #[test]
+    fn test_match_slug() {
+        assert_eq!(match_slug("/products/{slug}", "/products/runner"), Some("runner"));
+        assert_eq!(match_slug("/shop/{slug}.html", "/shop/runner.html"), Some("runner"));
+        assert_eq!(match_slug("/products/{slug}", "/products/runner/reviews"), None);
+        assert_eq!(match_slug("/products/{slug}", "/products/"), None);
+        assert_eq!(match_slug("/products/{slug}", "/categories/shoes"), None);
+    }
+
+    #[test]
+    fn test_normalize_source() {
+        assert_eq!(normalize_source("/blog/*").unwrap(), "/blog/*");
+        assert_eq!(normalize_source("/old-shop/").unwrap(), "/old-shop");
+        assert!(normalize_source("/blog/*/comments").is_err());
+        assert!(normalize_source("/blog/**").is_err());
+    }
+
+    #[test]
+    fn test_match_wildcard() {
+        assert_eq!(match_wildcard("/blog/", "/blog/2020/hello"), Some("2020/hello"));
+        assert_eq!(match_wildcard("/blog/", "/blog"), Some(""));
+        assert_eq!(match_wildcard("/blog/", "/blogroll"), None);
+        assert_eq!(match_wildcard("/shop-", "/shop-runner"), Some("runner"));
+        assert_eq!(match_wildcard("/", "/anything"), Some("anything"));
+    }
+
+    #[test]
+    fn test_check_redirect() {
+        assert!(check_redirect("/blog/*", "https://blog.example.com/*").is_ok());
+        assert!(check_redirect("/old/*", "/new").is_ok());
+        assert!(check_redirect("/sale", "/sale").is_err());
+        assert!(check_redirect("/old/*", "/old/new/*").is_err());
+        assert!(check_redirect("/old/*", "/old").is_err());
+        assert!(check_redirect("/old", "/new/*").is_err());
+        assert!(check_redirect("/old/*", "/new/*/*").is_err());
+    }
+
+    fn redirect(source_path: &str, target_path: &str) -> UrlRedirect {
+        UrlRedirect {
+            id: Uuid::new_v4(),
+            source_path: source_path.to_string(),
+            target_path: target_path.to_string(),
+            status_code: 301,
+            hits: 0,
+            last_hit_at: None,
+            created_by: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_redirect_table_lookup() {
+        let table = RedirectTable::new(vec![
+            redirect("/blog/*", "https://blog.example.com/*"),
+            redirect("/blog/archive/*", "/news"),
+            redirect("/blog/welcome", "/about"),
+        ]);
+        let location = |path| table.lookup(path).map(|(_, location)| location);
+
+        assert_eq!(location("/blog/welcome").as_deref(), Some("/about"));
+        assert_eq!(location("/blog/archive/2019/recap").as_deref(), Some("/news"));
+        assert_eq!(location("/blog/2020/hello").as_deref(), Some("https://blog.example.com/2020/hello"));
+        assert_eq!(location("/blog").as_deref(), Some("https://blog.example.com/"));
+        assert_eq!(location("/products/runner"), None);
+    }
+
+    #[test]
+    fn test_parse_redirect_csv() {
+        let data = "\u{feff}Redirect from,Redirect to,Code\n\
+                    https://old.example.com/pages/about-us,/pages/about,\n\
+                    shoes/runner.html,/products/runner,302\n\
+                    \n\
+                    /sale,/offers,moved\n";
+        let (rows, errors) = parse_redirect_csv(data).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, 2);
+        assert_eq!(rows[0].1.source_path, "/pages/about-us");
+        assert_eq!(rows[0].1.status_code, None);
+        assert_eq!(rows[1].1.source_path, "/shoes/runner.html");
+        assert_eq!(rows[1].1.target_path, "/products/runner");
+        assert_eq!(rows[1].1.status_code, Some(302));
+        assert_eq!(errors, vec![RedirectImportError { line: 5, message: "Invalid status code moved".to_string() }]);
+
+        assert!(parse_redirect_csv("path,name\n/a,b\n").is_err());
+    }
+
+    #[test]
+    fn test_check_status_code() {
+        assert_eq!(check_status_code(308).unwrap(), 308);
+        assert!(check_status_code(200).is_err());
+    }
+}
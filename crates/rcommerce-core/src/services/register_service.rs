@@ -0,0 +1,193 @@
+//! POS Register Service
+//!
+//! Cash drawer sessions for the point-of-sale channel. Staff open a session
+//! on a register with a cash float, record each tender taken or refunded
+//! and any cash paid in or out, and close it with the counted cash. Closing
+//! stores the expected cash and over/short; the session's Z-report breaks
+//! the takings down by tender and can be exported as CSV.
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    CloseRegisterSessionRequest, CreateRegisterRequest, CustomerRole, OpenRegisterSessionRequest, PosRegister,
+    RecordRegisterTransactionRequest, RegisterSession, RegisterSessionStatus, RegisterTransaction, ZReport,
+};
+use crate::repository::RegisterRepository;
+use crate::{Error, Result};
+
+/// Sessions listed per register
+const SESSION_LIST_LIMIT: i64 = 50;
+
+/// POS register service
+pub struct RegisterService<R: RegisterRepository> {
+    repository: R,
+}
+
+impl<R: RegisterRepository> RegisterService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Create a register (admins and managers)
+    pub async fn create_register(&self, actor_id: Uuid, request: CreateRegisterRequest) -> Result<PosRegister> {
+        self.require_staff(actor_id).await?;
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.name.trim().is_empty() {
+            return Err(Error::validation("Register name must not be empty"));
+        }
+        self.repository.create_register(&request).await
+    }
+
+    /// List registers
+    pub async fn list_registers(&self, actor_id: Uuid) -> Result<Vec<PosRegister>> {
+        self.require_staff(actor_id).await?;
+        self.repository.list_registers().await
+    }
+
+    /// Open a session on a register
+    pub async fn open_session(
+        &self,
+        actor_id: Uuid,
+        register_id: Uuid,
+        request: OpenRegisterSessionRequest,
+    ) -> Result<RegisterSession> {
+        self.require_staff(actor_id).await?;
+        if request.opening_float < Decimal::ZERO {
+            return Err(Error::validation("Opening float cannot be negative"));
+        }
+
+        let register = self
+            .repository
+            .find_register(register_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Register not found"))?;
+        if !register.is_active {
+            return Err(Error::validation("Register is inactive"));
+        }
+
+        self.repository
+            .open_session(&register, request.opening_float, actor_id, request.notes.as_deref())
+            .await?
+            .ok_or_else(|| Error::HttpError(http::StatusCode::CONFLICT, "Register already has an open session".to_string()))
+    }
+
+    /// A register's sessions, newest first
+    pub async fn list_sessions(&self, actor_id: Uuid, register_id: Uuid) -> Result<Vec<RegisterSession>> {
+        self.require_staff(actor_id).await?;
+        self.repository.list_sessions(register_id, SESSION_LIST_LIMIT).await
+    }
+
+    /// Record a sale, refund or drawer movement in an open session
+    pub async fn record_transaction(
+        &self,
+        actor_id: Uuid,
+        session_id: Uuid,
+        request: RecordRegisterTransactionRequest,
+    ) -> Result<RegisterTransaction> {
+        self.require_staff(actor_id).await?;
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.amount <= Decimal::ZERO {
+            return Err(Error::validation("Amount must be positive"));
+        }
+        let method = request
+            .tender()
+            .ok_or_else(|| Error::validation("A payment method is required for sales and refunds"))?;
+
+        self.session(session_id).await?;
+        self.repository
+            .record_transaction(
+                session_id,
+                request.kind,
+                &method,
+                request.amount,
+                request.order_id,
+                request.reference.as_deref(),
+                actor_id,
+            )
+            .await?
+            .ok_or_else(|| Error::validation("Register session is closed"))
+    }
+
+    /// A session's transactions
+    pub async fn transactions(&self, actor_id: Uuid, session_id: Uuid) -> Result<Vec<RegisterTransaction>> {
+        self.require_staff(actor_id).await?;
+        self.session(session_id).await?;
+        self.repository.transactions(session_id).await
+    }
+
+    /// Close a session with the counted cash
+    pub async fn close_session(
+        &self,
+        actor_id: Uuid,
+        session_id: Uuid,
+        request: CloseRegisterSessionRequest,
+    ) -> Result<ZReport> {
+        self.require_staff(actor_id).await?;
+        if request.counted_cash < Decimal::ZERO {
+            return Err(Error::validation("Counted cash cannot be negative"));
+        }
+
+        self.session(session_id).await?;
+        let session = self
+            .repository
+            .close_session(session_id, request.counted_cash, actor_id, request.notes.as_deref())
+            .await?
+            .ok_or_else(|| Error::validation("Register session is already closed"))?;
+        self.build_report(session).await
+    }
+
+    /// Running totals for a session; for closed sessions this is the Z-report
+    pub async fn session_report(&self, actor_id: Uuid, session_id: Uuid) -> Result<ZReport> {
+        self.require_staff(actor_id).await?;
+        let session = self.session(session_id).await?;
+        self.build_report(session).await
+    }
+
+    /// Z-report of a closed session
+    pub async fn z_report(&self, actor_id: Uuid, session_id: Uuid) -> Result<ZReport> {
+        self.require_staff(actor_id).await?;
+        let session = self.session(session_id).await?;
+        if session.status != RegisterSessionStatus::Closed {
+            return Err(Error::validation("Z-reports are available once the session is closed"));
+        }
+        self.build_report(session).await
+    }
+
+    async fn build_report(&self, session: RegisterSession) -> Result<ZReport> {
+        let register = self
+            .repository
+            .find_register(session.register_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Register not found"))?;
+        let transactions = self.repository.transactions(session.id).await?;
+        Ok(ZReport::build(&session, &register.name, &transactions))
+    }
+
+    async fn session(&self, session_id: Uuid) -> Result<RegisterSession> {
+        self.repository
+            .find_session(session_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Register session not found"))
+    }
+
+    async fn require_staff(&self, actor_id: Uuid) -> Result<()> {
+        let role = self
+            .repository
+            .actor_role(actor_id)
+            .await?
+            .ok_or_else(|| Error::unauthorized("Unknown user"))?;
+        if role == CustomerRole::Customer {
+            return Err(Error::HttpError(
+                http::StatusCode::FORBIDDEN,
+                "Only staff can operate registers".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
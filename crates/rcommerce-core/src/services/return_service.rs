@@ -0,0 +1,453 @@
+//! Return (RMA) Service
+//!
+//! Customers request returns for order lines within the configured window;
+//! staff approve or reject them. Approval generates a prepaid return label
+//! through the shipping carriers (staff can retry or pick another carrier if
+//! that fails), and receiving the parcel refunds the items to the order's
+//! payment through its gateway. Customers are emailed at each decision.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::ReturnsConfig;
+use crate::models::{
+    CreateReturnRequest, NewReturnItem, ReturnAuthorization, ReturnDecision, ReturnDetails, ReturnLabelRequest,
+    ReturnStatus,
+};
+use crate::notification::{EmailNotificationFactory, Notification};
+use crate::payment::agnostic::{PaymentService, RefundResponse, RefundStatus};
+use crate::repository::{NotificationRepository, OrderPayment, ReturnRepository};
+use crate::services::FormattingService;
+use crate::shipping::{Package, ShippingProviderFactory};
+use crate::{Error, Result};
+
+/// Refunds a received return to the order's payment
+#[async_trait]
+pub trait ReturnRefunder: Send + Sync {
+    async fn refund(&self, payment: &OrderPayment, rma: &ReturnAuthorization) -> Result<RefundResponse>;
+}
+
+/// Refunds returns through the payment's gateway
+pub struct GatewayRefunder {
+    payments: Arc<PaymentService>,
+}
+
+impl GatewayRefunder {
+    pub fn new(payments: Arc<PaymentService>) -> Self {
+        Self { payments }
+    }
+}
+
+#[async_trait]
+impl ReturnRefunder for GatewayRefunder {
+    async fn refund(&self, payment: &OrderPayment, rma: &ReturnAuthorization) -> Result<RefundResponse> {
+        let gateway = self
+            .payments
+            .get_gateway(Some(&payment.gateway))
+            .ok_or_else(|| Error::payment_error(format!("Payment gateway '{}' is not configured", payment.gateway)))?;
+        let gateway_payment_id = payment
+            .gateway_payment_id
+            .as_deref()
+            .ok_or_else(|| Error::payment_error("The order's payment has no gateway reference"))?;
+
+        let response = gateway
+            .refund_payment(gateway_payment_id, Some(rma.refund_amount), &format!("Return {}", rma.rma_number))
+            .await?;
+        if response.status == RefundStatus::Failed {
+            return Err(Error::payment_error(format!("Refund for return {} failed", rma.rma_number)));
+        }
+        Ok(response)
+    }
+}
+
+/// Return service
+pub struct ReturnService<R: ReturnRepository> {
+    repository: R,
+    config: ReturnsConfig,
+    shipping: Option<Arc<ShippingProviderFactory>>,
+    default_provider: Option<String>,
+    refunder: Option<Arc<dyn ReturnRefunder>>,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+    formatting: Arc<FormattingService>,
+}
+
+impl<R: ReturnRepository> ReturnService<R> {
+    pub fn new(repository: R, config: ReturnsConfig) -> Self {
+        Self {
+            repository,
+            config,
+            shipping: None,
+            default_provider: None,
+            refunder: None,
+            notifications: None,
+            formatting: Arc::new(FormattingService::default()),
+        }
+    }
+
+    /// Generate return labels with the shipping carriers; `default_provider`
+    /// is used when `[returns] label_provider` is not set
+    pub fn with_labels(mut self, shipping: Arc<ShippingProviderFactory>, default_provider: Option<String>) -> Self {
+        self.shipping = Some(shipping);
+        self.default_provider = default_provider;
+        self
+    }
+
+    /// Refund received returns (without it, returns stay received until refunded elsewhere)
+    pub fn with_refunds(mut self, refunder: Arc<dyn ReturnRefunder>) -> Self {
+        self.refunder = Some(refunder);
+        self
+    }
+
+    /// Queue customer emails, formatting amounts with `formatting`
+    pub fn with_notifications(
+        mut self,
+        notifications: Arc<dyn NotificationRepository>,
+        formatting: Arc<FormattingService>,
+    ) -> Self {
+        self.notifications = Some(notifications);
+        self.formatting = formatting;
+        self
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Request a return; `customer_id` is the requesting customer (None when staff file it)
+    pub async fn request_return(
+        &self,
+        order_id: Uuid,
+        customer_id: Option<Uuid>,
+        request: CreateReturnRequest,
+    ) -> Result<ReturnDetails> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+
+        let order = self
+            .repository
+            .order(order_id)
+            .await?
+            .filter(|order| customer_id.is_none() || order.customer_id == customer_id)
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+
+        if self.config.window_days > 0
+            && Utc::now() - order.created_at > Duration::days(i64::from(self.config.window_days))
+        {
+            return Err(Error::validation(format!(
+                "Returns must be requested within {} days of ordering",
+                self.config.window_days
+            )));
+        }
+
+        let returnable = self.repository.returnable_items(order_id).await?;
+        let items = NewReturnItem::plan(&returnable, &request.items).map_err(Error::validation)?;
+
+        let rma = self.repository.create(&order, &request, &items, customer_id).await?;
+        tracing::info!("Return {} requested for order {}", rma.rma_number, order.order_number);
+        self.details(rma).await
+    }
+
+    /// Get a return
+    pub async fn get(&self, id: Uuid) -> Result<ReturnAuthorization> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Return not found"))
+    }
+
+    /// Get a return with its lines and history
+    pub async fn get_details(&self, id: Uuid) -> Result<ReturnDetails> {
+        let rma = self.get(id).await?;
+        self.details(rma).await
+    }
+
+    /// Returns, newest first
+    pub async fn list(
+        &self,
+        status: Option<ReturnStatus>,
+        customer_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ReturnAuthorization>> {
+        self.repository.list(status, customer_id, limit).await
+    }
+
+    /// Cancel a return before it is received
+    pub async fn cancel(&self, id: Uuid, actor_id: Option<Uuid>, decision: ReturnDecision) -> Result<ReturnDetails> {
+        let rma = self.transition(id, ReturnStatus::Cancelled, actor_id, decision.notes.as_deref()).await?;
+        self.details(rma).await
+    }
+
+    /// Approve a return, generate its label and email the customer
+    pub async fn approve(&self, id: Uuid, actor_id: Uuid, decision: ReturnDecision) -> Result<ReturnDetails> {
+        let mut rma = self
+            .transition(id, ReturnStatus::Approved, Some(actor_id), decision.notes.as_deref())
+            .await?;
+
+        if self.shipping.is_some() {
+            match self.generate_label(&rma, ReturnLabelRequest::default()).await {
+                Ok(labelled) => rma = labelled,
+                Err(e) => tracing::warn!("Failed to create return label for {}: {}", rma.rma_number, e),
+            }
+        }
+
+        if let Err(e) = self.notify_approved(&rma).await {
+            tracing::warn!("Failed to notify customer of approved return {}: {}", rma.rma_number, e);
+        }
+        self.details(rma).await
+    }
+
+    /// Reject a return and email the customer
+    pub async fn reject(&self, id: Uuid, actor_id: Uuid, decision: ReturnDecision) -> Result<ReturnDetails> {
+        let rma = self
+            .transition(id, ReturnStatus::Rejected, Some(actor_id), decision.notes.as_deref())
+            .await?;
+
+        if let Err(e) = self.notify_rejected(&rma).await {
+            tracing::warn!("Failed to notify customer of rejected return {}: {}", rma.rma_number, e);
+        }
+        self.details(rma).await
+    }
+
+    /// (Re)generate the label for an approved return and email it to the customer
+    pub async fn create_label(&self, id: Uuid, request: ReturnLabelRequest) -> Result<ReturnAuthorization> {
+        let rma = self.get(id).await?;
+        if rma.status != ReturnStatus::Approved {
+            return Err(Error::validation(format!(
+                "Return {} must be approved before a label is created",
+                rma.rma_number
+            )));
+        }
+
+        let rma = self.generate_label(&rma, request).await?;
+        if let Err(e) = self.notify_approved(&rma).await {
+            tracing::warn!("Failed to send return label for {}: {}", rma.rma_number, e);
+        }
+        Ok(rma)
+    }
+
+    /// Mark a return's items received and refund them
+    ///
+    /// A failed refund leaves the return received; it can be retried with
+    /// [`ReturnService::refund`].
+    pub async fn receive(&self, id: Uuid, actor_id: Uuid, decision: ReturnDecision) -> Result<ReturnDetails> {
+        let mut rma = self
+            .transition(id, ReturnStatus::Received, Some(actor_id), decision.notes.as_deref())
+            .await?;
+
+        if self.refunder.is_some() {
+            match self.issue_refund(&rma, Some(actor_id)).await {
+                Ok(refunded) => rma = refunded,
+                Err(e) => tracing::warn!("Failed to refund return {}: {}", rma.rma_number, e),
+            }
+        }
+        self.details(rma).await
+    }
+
+    /// Refund a received return
+    pub async fn refund(&self, id: Uuid, actor_id: Uuid) -> Result<ReturnDetails> {
+        let rma = self.get(id).await?;
+        if rma.status != ReturnStatus::Received {
+            return Err(Error::validation(format!(
+                "Return {} must be received before it is refunded",
+                rma.rma_number
+            )));
+        }
+
+        let rma = self.issue_refund(&rma, Some(actor_id)).await?;
+        self.details(rma).await
+    }
+
+    async fn details(&self, rma: ReturnAuthorization) -> Result<ReturnDetails> {
+        let items = self.repository.items(rma.id).await?;
+        let events = self.repository.events(rma.id).await?;
+        Ok(ReturnDetails { rma, items, events })
+    }
+
+    async fn transition(
+        &self,
+        id: Uuid,
+        to: ReturnStatus,
+        actor_id: Option<Uuid>,
+        notes: Option<&str>,
+    ) -> Result<ReturnAuthorization> {
+        let rma = self.get(id).await?;
+        if !rma.status.can_transition_to(to) {
+            return Err(Error::validation(format!(
+                "Return {} is {:?} and cannot be moved to {:?}",
+                rma.rma_number, rma.status, to
+            )));
+        }
+
+        self.repository
+            .transition(id, rma.status, to, actor_id, notes)
+            .await?
+            .ok_or_else(|| Error::validation(format!("Return {} was changed by someone else, please retry", rma.rma_number)))
+    }
+
+    async fn generate_label(&self, rma: &ReturnAuthorization, request: ReturnLabelRequest) -> Result<ReturnAuthorization> {
+        let shipping = self
+            .shipping
+            .as_ref()
+            .ok_or_else(|| Error::shipping("Return labels are not configured"))?;
+        let provider_id = request
+            .provider_id
+            .or_else(|| self.config.label_provider.clone())
+            .or_else(|| self.default_provider.clone())
+            .ok_or_else(|| Error::shipping("No carrier configured for return labels"))?;
+        let provider = shipping.get(&provider_id)?;
+        let service_code = request
+            .service_code
+            .or_else(|| self.config.label_service.clone())
+            .or_else(|| {
+                provider
+                    .get_services()
+                    .into_iter()
+                    .find(|service| service.domestic)
+                    .map(|service| service.code)
+            })
+            .ok_or_else(|| Error::shipping(format!("{} has no service for return labels", provider.name())))?;
+
+        let to = self
+            .config
+            .address
+            .as_ref()
+            .ok_or_else(|| Error::config("[returns] address (or [shipping] origin) is required for return labels"))?
+            .to_address();
+        let from = self
+            .repository
+            .ship_from(rma.order_id)
+            .await?
+            .ok_or_else(|| Error::validation("The order has no shipping address to send the return from"))?;
+        let package = Package::new(self.config.label_weight, self.config.label_weight_unit.clone());
+
+        let shipment = provider.create_shipment(&from, &to, &package, &service_code, None).await?;
+        self.repository
+            .set_label(
+                rma.id,
+                provider.id(),
+                &service_code,
+                shipment.tracking_number.as_deref(),
+                shipment.label_url.as_deref(),
+            )
+            .await
+    }
+
+    async fn issue_refund(&self, rma: &ReturnAuthorization, actor_id: Option<Uuid>) -> Result<ReturnAuthorization> {
+        let refunder = self
+            .refunder
+            .as_ref()
+            .ok_or_else(|| Error::payment_error("Refunds are not configured"))?;
+        let payment = self
+            .repository
+            .order_payment(rma.order_id)
+            .await?
+            .ok_or_else(|| Error::payment_error("The order has no captured payment to refund"))?;
+
+        let response = refunder.refund(&payment, rma).await?;
+        let rma = self.repository.record_refund(rma, &payment, &response, actor_id).await?;
+        tracing::info!("Refunded {} {} for return {}", rma.refund_amount, rma.currency, rma.rma_number);
+
+        if let Err(e) = self.notify_refunded(&rma).await {
+            tracing::warn!("Failed to notify customer of refunded return {}: {}", rma.rma_number, e);
+        }
+        Ok(rma)
+    }
+
+    /// How to send the items back, for the approval email
+    fn return_instructions(&self, rma: &ReturnAuthorization) -> String {
+        let carrier = rma
+            .label_provider
+            .as_deref()
+            .and_then(|id| self.shipping.as_ref()?.get(id).ok())
+            .map(|provider| provider.name())
+            .unwrap_or("the carrier");
+
+        match (&rma.label_url, &self.config.address) {
+            (Some(url), _) => format!(
+                "Print your prepaid {} return label ({}), pack the items securely and drop the parcel off with {}.",
+                carrier, url, carrier
+            ),
+            (None, Some(address)) => format!(
+                "Pack the items securely, write {} on the parcel and send it to {}, {}, {}, {} {}, {}.",
+                rma.rma_number, address.name, address.address1, address.city, address.state, address.zip,
+                address.country
+            ),
+            (None, None) => format!(
+                "Reply to this email with {} and our team will send you return shipping instructions.",
+                rma.rma_number
+            ),
+        }
+    }
+
+    async fn notify_approved(&self, rma: &ReturnAuthorization) -> Result<()> {
+        let refund_amount = self.formatting.format_price(rma.refund_amount, &rma.currency, None);
+        let instructions = self.return_instructions(rma);
+        self.notify(rma, |email, name, order_number| {
+            EmailNotificationFactory::return_approved(
+                email,
+                name,
+                &rma.rma_number,
+                order_number,
+                &refund_amount,
+                &instructions,
+                rma.tracking_number.as_deref().unwrap_or("Not yet assigned"),
+                rma.decision_notes.as_deref().unwrap_or_default(),
+            )
+        })
+        .await
+    }
+
+    async fn notify_rejected(&self, rma: &ReturnAuthorization) -> Result<()> {
+        self.notify(rma, |email, name, order_number| {
+            EmailNotificationFactory::return_rejected(
+                email,
+                name,
+                &rma.rma_number,
+                order_number,
+                rma.decision_notes.as_deref().unwrap_or_default(),
+            )
+        })
+        .await
+    }
+
+    async fn notify_refunded(&self, rma: &ReturnAuthorization) -> Result<()> {
+        let refund_amount = self.formatting.format_price(rma.refund_amount, &rma.currency, None);
+        self.notify(rma, |email, name, order_number| {
+            EmailNotificationFactory::refund_processed(
+                email,
+                name,
+                order_number,
+                &refund_amount,
+                "Original payment method",
+                &format!("Your refund for return {} has been issued.", rma.rma_number),
+            )
+        })
+        .await
+    }
+
+    /// Queue an email to the order's customer
+    async fn notify<F>(&self, rma: &ReturnAuthorization, build: F) -> Result<()>
+    where
+        F: FnOnce(&str, &str, &str) -> Result<Notification>,
+    {
+        let Some(ref notifications) = self.notifications else {
+            return Ok(());
+        };
+        if !self.config.notify_customers {
+            return Ok(());
+        }
+
+        let order = self
+            .repository
+            .order(rma.order_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Order not found"))?;
+        let name = order.customer_name.as_deref().unwrap_or("Customer");
+        let notification = build(&order.email, name, &order.order_number)?;
+        notifications.create(&notification).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Role Service
+//!
+//! Manages staff roles and their assignment to accounts. Role permissions
+//! use the API key scope format, so an account's effective permissions (its
+//! base `CustomerRole` plus assigned roles) are checked with the same
+//! `ScopeChecker` as API keys. Admin routes look them up on every request,
+//! so assignments apply immediately; tokens pick them up on next login or
+//! refresh.
+
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{
+    CreateRoleRequest, EffectivePermissions, ResourceAccess, Role, RoleAssignment, UpdateRoleRequest,
+};
+use crate::repository::RoleRepository;
+use crate::services::api_key_scopes::{Action, Resource, Scope, ScopeChecker};
+use crate::{Error, Result};
+
+/// Every permission a role can grant
+pub fn permission_catalog() -> Vec<String> {
+    let mut permissions = vec![Action::Admin.as_str().to_string()];
+    for resource in Resource::all() {
+        for action in [Action::Read, Action::Write, Action::Admin] {
+            permissions.push(Scope::new(Some(resource), action).as_scope_string());
+        }
+    }
+    permissions
+}
+
+/// Per-resource access granted by `permissions`
+///
+/// With `staff`, only staff permissions count: the global `read` and
+/// `write` every customer account carries are ignored, as on admin routes.
+/// API keys are evaluated with `staff` false. Unparseable scopes grant
+/// nothing.
+pub fn resource_access(permissions: &[String], staff: bool) -> Vec<ResourceAccess> {
+    let checker = ScopeChecker::new(permissions).ok();
+    let allowed = |resource: Resource, action: Action| {
+        checker.as_ref().is_some_and(|checker| {
+            if staff {
+                checker.can_staff(resource, action)
+            } else {
+                checker.can(resource, action)
+            }
+        })
+    };
+
+    Resource::all()
+        .into_iter()
+        .map(|resource| ResourceAccess {
+            resource: resource.as_str().to_string(),
+            read: allowed(resource, Action::Read),
+            write: allowed(resource, Action::Write),
+            admin: allowed(resource, Action::Admin),
+        })
+        .collect()
+}
+
+/// Reject unknown scopes, and the global `read`/`write` scopes (they are
+/// what every customer account gets, not staff permissions)
+fn validate_permissions(permissions: &[String]) -> Result<()> {
+    for permission in permissions {
+        let scope = Scope::parse(permission).map_err(Error::validation)?;
+        if scope.resource.is_none() && scope.action != Action::Admin {
+            return Err(Error::validation(format!(
+                "'{}' applies to every resource; use resource permissions such as 'orders:{}'",
+                permission, permission
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Role service
+pub struct RoleService<R: RoleRepository> {
+    repository: R,
+}
+
+impl<R: RoleRepository> RoleService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// All roles
+    pub async fn list_roles(&self) -> Result<Vec<Role>> {
+        self.repository.list().await
+    }
+
+    /// Get a role
+    pub async fn get_role(&self, id: Uuid) -> Result<Role> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Role not found"))
+    }
+
+    /// Create a role
+    pub async fn create_role(&self, request: CreateRoleRequest) -> Result<Role> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        validate_permissions(&request.permissions)?;
+
+        let role = self.repository.create(&request).await?;
+        tracing::info!("Created role {} with permissions {:?}", role.name, role.permissions);
+        Ok(role)
+    }
+
+    /// Update a role; system roles keep their name
+    pub async fn update_role(&self, id: Uuid, request: UpdateRoleRequest) -> Result<Role> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if let Some(ref permissions) = request.permissions {
+            validate_permissions(permissions)?;
+        }
+
+        let role = self.get_role(id).await?;
+        if role.is_system && request.name.as_ref().is_some_and(|name| *name != role.name) {
+            return Err(Error::validation(format!("System role '{}' cannot be renamed", role.name)));
+        }
+
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Role not found"))
+    }
+
+    /// Delete a custom role, removing it from everyone who holds it
+    pub async fn delete_role(&self, id: Uuid) -> Result<()> {
+        let role = self.get_role(id).await?;
+        if role.is_system {
+            return Err(Error::validation(format!("System role '{}' cannot be deleted", role.name)));
+        }
+
+        self.repository.delete(id).await?;
+        tracing::info!("Deleted role {}", role.name);
+        Ok(())
+    }
+
+    /// Roles assigned to an account
+    pub async fn assignments(&self, customer_id: Uuid) -> Result<Vec<RoleAssignment>> {
+        self.repository.assignments(customer_id).await
+    }
+
+    /// Assign a role to an account
+    pub async fn assign(&self, customer_id: Uuid, role_id: Uuid, assigned_by: Uuid) -> Result<Vec<RoleAssignment>> {
+        let role = self.get_role(role_id).await?;
+        self.repository
+            .customer_role(customer_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Customer not found"))?;
+
+        self.repository.assign(customer_id, role_id, Some(assigned_by)).await?;
+        tracing::info!("{} assigned role {} to {}", assigned_by, role.name, customer_id);
+        self.repository.assignments(customer_id).await
+    }
+
+    /// Remove a role from an account
+    pub async fn unassign(&self, customer_id: Uuid, role_id: Uuid, removed_by: Uuid) -> Result<()> {
+        if !self.repository.unassign(customer_id, role_id).await? {
+            return Err(Error::not_found("Role assignment not found"));
+        }
+        tracing::info!("{} removed role {} from {}", removed_by, role_id, customer_id);
+        Ok(())
+    }
+
+    /// An account's effective permissions; None if the account does not exist
+    pub async fn effective_permissions(&self, customer_id: Uuid) -> Result<Option<EffectivePermissions>> {
+        let Some(base_role) = self.repository.customer_role(customer_id).await? else {
+            return Ok(None);
+        };
+        let roles = self.repository.assignments(customer_id).await?;
+        Ok(Some(EffectivePermissions::new(customer_id, base_role, roles)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_catalog() {
+        let catalog = permission_catalog();
+        assert!(catalog.contains(&"admin".to_string()));
+        assert!(catalog.contains(&"orders:write".to_string()));
+        assert!(catalog.contains(&"users:admin".to_string()));
+        assert_eq!(catalog.len(), 1 + Resource::all().len() * 3);
+    }
+
+    #[test]
+    fn test_resource_access() {
+        let permissions = vec!["read".to_string(), "orders:write".to_string()];
+        let find = |access: &[ResourceAccess], resource: &str| {
+            access.iter().find(|a| a.resource == resource).cloned().unwrap()
+        };
+
+        let staff = resource_access(&permissions, true);
+        assert_eq!(staff.len(), Resource::all().len());
+        let orders = find(&staff, "orders");
+        assert!(orders.read && orders.write && !orders.admin);
+        // The global read is not a staff permission
+        assert!(!find(&staff, "products").read);
+
+        let key = resource_access(&permissions, false);
+        assert!(find(&key, "products").read);
+        assert!(!find(&key, "products").write);
+
+        let admin = resource_access(&["admin".to_string()], true);
+        assert!(admin.iter().all(|a| a.read && a.write && a.admin));
+        assert!(resource_access(&["bogus".to_string()], false).iter().all(|a| !a.read));
+    }
+
+    #[test]
+    fn test_validate_permissions() {
+        assert!(validate_permissions(&["orders:write".to_string(), "admin".to_string()]).is_ok());
+        assert!(validate_permissions(&["write".to_string()]).is_err());
+        assert!(validate_permissions(&["orders:delete".to_string()]).is_err());
+        assert!(validate_permissions(&["widgets:read".to_string()]).is_err());
+    }
+}
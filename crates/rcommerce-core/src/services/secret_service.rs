@@ -0,0 +1,176 @@
+//! Secret Service
+//!
+//! Keeps the secrets stored in the database sealed under the current master
+//! key: plain-text values (from before a master key was configured) are
+//! sealed, and values sealed under a previous master key are rewrapped.
+//! Run after changing the master key, from `rcommerce secrets rotate` or
+//! when the server starts.
+
+use serde::Serialize;
+
+use crate::repository::SecretRepository;
+use crate::secrets::SecretBox;
+use crate::{Error, Result};
+
+/// Outcome of a re-encryption pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReencryptReport {
+    /// Secrets looked at
+    pub checked: usize,
+    /// Secrets sealed or rewrapped
+    pub resealed: usize,
+    /// Secrets changed by someone else during the pass (left for the next one)
+    pub skipped: usize,
+    /// Secrets that could not be opened, e.g. sealed under an unknown key
+    pub failed: usize,
+}
+
+/// Stored secret service
+pub struct SecretService<R: SecretRepository> {
+    repository: R,
+    secrets: SecretBox,
+}
+
+impl<R: SecretRepository> SecretService<R> {
+    pub fn new(repository: R, secrets: SecretBox) -> Self {
+        Self { repository, secrets }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn secrets(&self) -> &SecretBox {
+        &self.secrets
+    }
+
+    /// Secrets that are plain text or sealed under a previous master key
+    pub async fn pending(&self) -> Result<usize> {
+        Ok(self
+            .repository
+            .list()
+            .await?
+            .iter()
+            .filter(|secret| self.secrets.needs_reseal(&secret.value))
+            .count())
+    }
+
+    /// Seal or rewrap every secret not sealed under the current master key;
+    /// with `dry_run` only count them
+    pub async fn reencrypt(&self, dry_run: bool) -> Result<ReencryptReport> {
+        if !self.secrets.is_enabled() {
+            return Err(Error::Config("No master key is configured (see [secrets])".to_string()));
+        }
+
+        let mut report = ReencryptReport::default();
+        for secret in self.repository.list().await? {
+            report.checked += 1;
+            if !self.secrets.needs_reseal(&secret.value) {
+                continue;
+            }
+            let resealed = match self.secrets.reseal(&secret.value) {
+                Ok(resealed) => resealed,
+                Err(e) => {
+                    tracing::error!("Cannot re-encrypt {} {}: {}", secret.kind.as_str(), secret.id, e);
+                    report.failed += 1;
+                    continue;
+                }
+            };
+            if dry_run || self.repository.replace(&secret, &resealed).await? {
+                report.resealed += 1;
+            } else {
+                report.skipped += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{SecretKind, StoredSecret};
+    use crate::secrets::MasterKey;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    struct MockRepository {
+        secrets: Mutex<Vec<StoredSecret>>,
+    }
+
+    impl MockRepository {
+        fn with(values: &[&str]) -> Self {
+            let secrets = values
+                .iter()
+                .map(|value| StoredSecret {
+                    kind: SecretKind::WebhookSigningSecret,
+                    id: Uuid::new_v4(),
+                    value: value.to_string(),
+                })
+                .collect();
+            Self { secrets: Mutex::new(secrets) }
+        }
+
+        fn values(&self) -> Vec<String> {
+            self.secrets.lock().unwrap().iter().map(|s| s.value.clone()).collect()
+        }
+    }
+
+    #[async_trait]
+    impl SecretRepository for MockRepository {
+        async fn list(&self) -> Result<Vec<StoredSecret>> {
+            Ok(self.secrets.lock().unwrap().clone())
+        }
+
+        async fn replace(&self, secret: &StoredSecret, value: &str) -> Result<bool> {
+            let mut secrets = self.secrets.lock().unwrap();
+            match secrets.iter_mut().find(|s| s.id == secret.id && s.value == secret.value) {
+                Some(stored) => {
+                    stored.value = value.to_string();
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+    }
+
+    fn key(byte: u8) -> MasterKey {
+        MasterKey::from_bytes([byte; 32])
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_after_rotation() {
+        let old = SecretBox::new(key(1), Vec::new());
+        let sealed_old = old.seal("old-secret").unwrap();
+        let unknown = SecretBox::new(key(9), Vec::new()).seal("lost").unwrap();
+
+        let rotated = SecretBox::new(key(2), vec![key(1)]);
+        let sealed_current = rotated.seal("current").unwrap();
+        let repo = MockRepository::with(&["plain-secret", &sealed_old, &sealed_current, &unknown]);
+        let service = SecretService::new(repo, rotated.clone());
+        assert_eq!(service.pending().await.unwrap(), 3);
+
+        let report = service.reencrypt(true).await.unwrap();
+        assert_eq!(report, ReencryptReport { checked: 4, resealed: 2, skipped: 0, failed: 1 });
+        assert_eq!(service.repository().values()[0], "plain-secret");
+
+        let report = service.reencrypt(false).await.unwrap();
+        assert_eq!(report.resealed, 2);
+        assert_eq!(report.failed, 1);
+
+        let values = service.repository().values();
+        let current_only = SecretBox::new(key(2), Vec::new());
+        assert_eq!(current_only.open(&values[0]).unwrap(), "plain-secret");
+        assert_eq!(current_only.open(&values[1]).unwrap(), "old-secret");
+        assert_eq!(values[2], sealed_current);
+        assert_eq!(service.pending().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_needs_master_key() {
+        let service = SecretService::new(MockRepository::with(&["plain"]), SecretBox::disabled());
+        assert!(service.reencrypt(false).await.is_err());
+        assert_eq!(service.pending().await.unwrap(), 0);
+    }
+}
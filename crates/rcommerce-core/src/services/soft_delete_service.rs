@@ -0,0 +1,190 @@
+//! Soft Delete Service
+//!
+//! Deleting and restoring products, customers and orders, listing the
+//! deleted ones, and the purge job that removes them for good after
+//! `retention_days`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::config::SoftDeleteConfig;
+use crate::jobs::recurring::RecurringJob;
+use crate::models::{DeletedRecord, SoftDeleteKind, SoftDeletePurge};
+use crate::repository::SoftDeleteRepository;
+use crate::{Error, Result};
+
+/// Deleted records returned by one listing at most
+pub const MAX_LIST_LIMIT: i64 = 1000;
+
+/// Soft delete service
+pub struct SoftDeleteService<R: SoftDeleteRepository> {
+    repository: R,
+    config: SoftDeleteConfig,
+}
+
+impl<R: SoftDeleteRepository> SoftDeleteService<R> {
+    pub fn new(repository: R, config: SoftDeleteConfig) -> Self {
+        Self { repository, config }
+    }
+
+    pub fn config(&self) -> &SoftDeleteConfig {
+        &self.config
+    }
+
+    /// Mark a record deleted; it disappears from lookups and listings
+    pub async fn delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<()> {
+        if !self.repository.soft_delete(kind, id).await? {
+            return Err(Error::not_found(format!("{} not found", kind.noun())));
+        }
+        Ok(())
+    }
+
+    /// Bring back a deleted record
+    pub async fn restore(&self, kind: SoftDeleteKind, id: Uuid) -> Result<()> {
+        if !self.repository.restore(kind, id).await? {
+            return Err(Error::not_found(format!("No deleted {} with this ID", kind.noun().to_lowercase())));
+        }
+        Ok(())
+    }
+
+    /// Deleted records, most recently deleted first
+    pub async fn list_deleted(&self, kind: SoftDeleteKind, limit: i64) -> Result<Vec<DeletedRecord>> {
+        self.repository.list_deleted(kind, limit.clamp(1, MAX_LIST_LIMIT)).await
+    }
+
+    /// Remove records deleted more than `retention_days` ago, up to
+    /// `purge_batch_size` of each kind. Records other data still refers to
+    /// are skipped and retried on the next run.
+    pub async fn purge_expired(&self) -> Result<SoftDeletePurge> {
+        let mut summary = SoftDeletePurge::default();
+        if self.config.retention_days == 0 {
+            return Ok(summary);
+        }
+        let cutoff = Utc::now() - Duration::days(i64::from(self.config.retention_days));
+        for kind in SoftDeleteKind::ALL {
+            for id in self.repository.expired(kind, cutoff, self.config.purge_batch_size).await? {
+                match self.repository.hard_delete(kind, id).await {
+                    Ok(true) => summary.purged += 1,
+                    Ok(false) => {}
+                    Err(e) => {
+                        tracing::warn!("Kept deleted {} {}: {}", kind, id, e);
+                        summary.skipped += 1;
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// The `soft_delete_purge` recurring job
+pub struct SoftDeletePurgeJob<R: SoftDeleteRepository> {
+    soft_deletes: Arc<SoftDeleteService<R>>,
+}
+
+impl<R: SoftDeleteRepository> SoftDeletePurgeJob<R> {
+    pub fn new(soft_deletes: Arc<SoftDeleteService<R>>) -> Self {
+        Self { soft_deletes }
+    }
+}
+
+#[async_trait]
+impl<R: SoftDeleteRepository + 'static> RecurringJob for SoftDeletePurgeJob<R> {
+    fn name(&self) -> &str {
+        "soft_delete_purge"
+    }
+
+    fn description(&self) -> &str {
+        "Permanently delete products, customers and orders deleted longer ago than the retention period"
+    }
+
+    fn default_schedule(&self) -> String {
+        "30 4 * * *".to_string()
+    }
+
+    async fn run(&self) -> Result<String> {
+        let purge = self.soft_deletes.purge_expired().await?;
+        Ok(format!("purged {} records, kept {} still referenced", purge.purged, purge.skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use std::sync::Mutex;
+
+    /// Deleted records; the ones in `referenced` can't be purged
+    #[derive(Default)]
+    struct MockRepository {
+        deleted: Mutex<Vec<(SoftDeleteKind, Uuid)>>,
+        referenced: Vec<Uuid>,
+    }
+
+    #[async_trait]
+    impl SoftDeleteRepository for MockRepository {
+        async fn soft_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+            let mut deleted = self.deleted.lock().unwrap();
+            if deleted.contains(&(kind, id)) {
+                return Ok(false);
+            }
+            deleted.push((kind, id));
+            Ok(true)
+        }
+
+        async fn restore(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+            let mut deleted = self.deleted.lock().unwrap();
+            let before = deleted.len();
+            deleted.retain(|record| *record != (kind, id));
+            Ok(deleted.len() < before)
+        }
+
+        async fn list_deleted(&self, _kind: SoftDeleteKind, _limit: i64) -> Result<Vec<DeletedRecord>> {
+            Ok(Vec::new())
+        }
+
+        async fn expired(&self, kind: SoftDeleteKind, _before: DateTime<Utc>, _limit: i64) -> Result<Vec<Uuid>> {
+            let deleted = self.deleted.lock().unwrap();
+            Ok(deleted.iter().filter(|(k, _)| *k == kind).map(|(_, id)| *id).collect())
+        }
+
+        async fn hard_delete(&self, kind: SoftDeleteKind, id: Uuid) -> Result<bool> {
+            if self.referenced.contains(&id) {
+                return Err(Error::Other("still referenced".to_string()));
+            }
+            self.restore(kind, id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_restore() {
+        let service = SoftDeleteService::new(MockRepository::default(), SoftDeleteConfig::default());
+        let id = Uuid::new_v4();
+        service.delete(SoftDeleteKind::Products, id).await.unwrap();
+        assert!(service.delete(SoftDeleteKind::Products, id).await.is_err());
+        assert!(service.restore(SoftDeleteKind::Customers, id).await.is_err());
+        service.restore(SoftDeleteKind::Products, id).await.unwrap();
+        assert!(service.restore(SoftDeleteKind::Products, id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_skips_referenced_records() {
+        let kept = Uuid::new_v4();
+        let repository = MockRepository { referenced: vec![kept], ..Default::default() };
+        let service = SoftDeleteService::new(repository, SoftDeleteConfig::default());
+        service.delete(SoftDeleteKind::Products, kept).await.unwrap();
+        service.delete(SoftDeleteKind::Products, Uuid::new_v4()).await.unwrap();
+        service.delete(SoftDeleteKind::Orders, Uuid::new_v4()).await.unwrap();
+
+        let purge = service.purge_expired().await.unwrap();
+        assert_eq!(purge, SoftDeletePurge { purged: 2, skipped: 1 });
+
+        let config = SoftDeleteConfig { retention_days: 0, ..Default::default() };
+        let service = SoftDeleteService::new(MockRepository::default(), config);
+        service.delete(SoftDeleteKind::Orders, Uuid::new_v4()).await.unwrap();
+        assert_eq!(service.purge_expired().await.unwrap(), SoftDeletePurge::default());
+    }
+}
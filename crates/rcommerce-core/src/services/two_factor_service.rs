@@ -0,0 +1,671 @@
+//! Two-Factor Authentication Service
+//!
+//! TOTP (RFC 6238: HMAC-SHA1, 6 digits, 30-second steps) as a second login
+//! factor, with one-time backup codes for a lost phone. Enrollment stores a
+//! pending secret that only takes effect once a code from the app confirms
+//! it. A login that passed the password check gets a short-lived challenge
+//! token to send back with the code. A code's step is never accepted twice,
+//! and repeated wrong codes lock the account's 2FA for `lockout_mins`.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use ring::hmac;
+use uuid::Uuid;
+
+use crate::cache::auth_session::constant_time_eq;
+use crate::config::TwoFactorConfig;
+use crate::models::{
+    hash_two_factor_token, LoginDevice, TwoFactorChallenge, TwoFactorLoginKind, TwoFactorProof,
+    TwoFactorSetup, TwoFactorStatus, TWO_FACTOR_CHALLENGE_PREFIX,
+};
+use crate::repository::TwoFactorRepository;
+use crate::secrets::SecretBox;
+use crate::{Error, Result};
+
+/// Seconds per TOTP step
+const STEP_SECS: i64 = 30;
+
+/// Digits in a TOTP code
+const CODE_DIGITS: u32 = 6;
+
+/// Bytes of a TOTP secret (160 bits, as RFC 4226 recommends)
+const SECRET_BYTES: usize = 20;
+
+/// Random characters after the prefix of a challenge token
+const CHALLENGE_TOKEN_LENGTH: usize = 48;
+
+/// Backup code alphabet, without look-alikes (0/o, 1/l/i)
+const BACKUP_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Characters per half of a backup code ("xxxxx-xxxxx")
+const BACKUP_CODE_HALF: usize = 5;
+
+/// Expired challenges are kept this long before being purged
+const PURGE_AFTER_HOURS: i64 = 24;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A challenge and its token; the token is only known here
+#[derive(Debug, Clone)]
+pub struct IssuedTwoFactorChallenge {
+    pub challenge: TwoFactorChallenge,
+    pub token: String,
+}
+
+/// Two-factor authentication service
+pub struct TwoFactorService<R: TwoFactorRepository> {
+    repository: R,
+    secrets: SecretBox,
+    config: TwoFactorConfig,
+}
+
+impl<R: TwoFactorRepository> TwoFactorService<R> {
+    pub fn new(repository: R, secrets: SecretBox, config: TwoFactorConfig) -> Self {
+        Self { repository, secrets, config }
+    }
+
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    pub fn config(&self) -> &TwoFactorConfig {
+        &self.config
+    }
+
+    /// A customer's two-factor state
+    pub async fn status(&self, customer_id: Uuid) -> Result<TwoFactorStatus> {
+        let credential = self.repository.find(customer_id).await?;
+        let enabled = credential.as_ref().is_some_and(|c| c.is_enabled());
+        let backup_codes_remaining = if enabled {
+            self.repository.remaining_backup_codes(customer_id).await?
+        } else {
+            0
+        };
+        Ok(TwoFactorStatus {
+            enabled,
+            pending: credential.as_ref().is_some_and(|c| !c.is_enabled()),
+            enabled_at: credential.as_ref().and_then(|c| c.confirmed_at),
+            backup_codes_remaining,
+            locked_until: credential.as_ref().and_then(|c| c.locked_until).filter(|until| *until > Utc::now()),
+        })
+    }
+
+    /// Whether logins of this customer need a second factor
+    pub async fn is_enabled(&self, customer_id: Uuid) -> Result<bool> {
+        Ok(self.repository.find(customer_id).await?.is_some_and(|c| c.is_enabled()))
+    }
+
+    /// Whether an access token issued at `issued_at` (seconds) may reach
+    /// admin routes when 2FA is required there: 2FA must be enabled, and
+    /// have been before the token's login
+    pub async fn admin_access_allowed(&self, customer_id: Uuid, issued_at: i64) -> Result<bool> {
+        Ok(self
+            .repository
+            .find(customer_id)
+            .await?
+            .and_then(|c| c.confirmed_at)
+            .is_some_and(|confirmed_at| confirmed_at.timestamp() <= issued_at))
+    }
+
+    /// Generate a new secret for the customer to add to an authenticator app
+    pub async fn begin_enrollment(&self, customer_id: Uuid, email: &str) -> Result<TwoFactorSetup> {
+        let secret = base32_encode(&rand::thread_rng().gen::<[u8; SECRET_BYTES]>());
+        let sealed = self.secrets.seal(&secret)?;
+        if !self.repository.start_enrollment(customer_id, &sealed).await? {
+            return Err(Error::validation("Two-factor authentication is already enabled"));
+        }
+        let provisioning_uri = provisioning_uri(&self.config.issuer, email, &secret);
+        Ok(TwoFactorSetup { secret, provisioning_uri })
+    }
+
+    /// Enable 2FA with a first code from the app; returns the backup codes,
+    /// which are only shown now
+    pub async fn confirm_enrollment(&self, customer_id: Uuid, code: &str, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let credential = self
+            .repository
+            .find(customer_id)
+            .await?
+            .filter(|c| !c.is_enabled())
+            .ok_or_else(|| Error::validation("No two-factor enrollment is pending"))?;
+        let secret = self.open_secret(&credential.secret)?;
+        let step = matching_step(&secret, code, now, self.config.allowed_drift_steps)
+            .ok_or_else(|| Error::validation("Invalid code; check the authenticator app's clock"))?;
+
+        let codes = generate_backup_codes(self.config.backup_codes);
+        if !self.repository.confirm(customer_id, step, &hash_backup_codes(&codes)).await? {
+            return Err(Error::validation("No two-factor enrollment is pending"));
+        }
+        tracing::info!("Customer {} enabled two-factor authentication", customer_id);
+        Ok(codes)
+    }
+
+    /// Turn 2FA off; needs a current code or a backup code
+    pub async fn disable(&self, customer_id: Uuid, proof: &TwoFactorProof, now: DateTime<Utc>) -> Result<()> {
+        self.verify(customer_id, proof, now).await?;
+        self.repository.disable(customer_id).await?;
+        tracing::info!("Customer {} disabled two-factor authentication", customer_id);
+        Ok(())
+    }
+
+    /// Turn a customer's 2FA off without a code, e.g. when an admin resets a lost device
+    pub async fn reset(&self, customer_id: Uuid) -> Result<()> {
+        if !self.repository.disable(customer_id).await? {
+            return Err(Error::not_found("Two-factor authentication is not enabled for this customer"));
+        }
+        tracing::info!("Two-factor authentication of customer {} was reset", customer_id);
+        Ok(())
+    }
+
+    /// Replace the backup codes; needs a current code or a backup code
+    pub async fn regenerate_backup_codes(
+        &self,
+        customer_id: Uuid,
+        proof: &TwoFactorProof,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>> {
+        self.verify(customer_id, proof, now).await?;
+        let codes = generate_backup_codes(self.config.backup_codes);
+        self.repository.replace_backup_codes(customer_id, &hash_backup_codes(&codes)).await?;
+        Ok(codes)
+    }
+
+    /// Open a challenge for a login that passed the password check
+    pub async fn start_challenge(
+        &self,
+        customer_id: Uuid,
+        kind: TwoFactorLoginKind,
+        device: &LoginDevice,
+        now: DateTime<Utc>,
+    ) -> Result<IssuedTwoFactorChallenge> {
+        let token = generate_challenge_token();
+        let expires_at = now + Duration::seconds(self.config.challenge_ttl_secs as i64);
+        let challenge = self
+            .repository
+            .create_challenge(customer_id, &hash_two_factor_token(&token), kind, device, expires_at)
+            .await?;
+        Ok(IssuedTwoFactorChallenge { challenge, token })
+    }
+
+    /// Answer a challenge; returns it, used up, when the code is right
+    pub async fn verify_challenge(
+        &self,
+        token: &str,
+        proof: &TwoFactorProof,
+        now: DateTime<Utc>,
+    ) -> Result<TwoFactorChallenge> {
+        let expired = || Error::unauthorized("Two-factor challenge has expired; please log in again");
+        let challenge = self
+            .repository
+            .find_challenge(&hash_two_factor_token(token))
+            .await?
+            .filter(|challenge| challenge.is_open(now))
+            .ok_or_else(expired)?;
+        if challenge.attempts >= self.config.max_challenge_attempts as i32 {
+            return Err(expired());
+        }
+
+        if let Err(e) = self.verify(challenge.customer_id, proof, now).await {
+            if matches!(e, Error::Unauthorized(_)) {
+                self.repository.record_challenge_attempt(challenge.id).await?;
+            }
+            return Err(e);
+        }
+        if !self.repository.consume_challenge(challenge.id).await? {
+            return Err(expired());
+        }
+        Ok(challenge)
+    }
+
+    /// Delete challenges that expired a while ago; returns how many
+    pub async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        self.repository.purge_challenges(now - Duration::hours(PURGE_AFTER_HOURS)).await
+    }
+
+    /// Check a code or backup code against an enabled credential, counting failures
+    async fn verify(&self, customer_id: Uuid, proof: &TwoFactorProof, now: DateTime<Utc>) -> Result<()> {
+        let credential = self
+            .repository
+            .find(customer_id)
+            .await?
+            .filter(|c| c.is_enabled())
+            .ok_or_else(|| Error::validation("Two-factor authentication is not enabled"))?;
+        if credential.is_locked(now) {
+            return Err(Error::HttpError(
+                http::StatusCode::TOO_MANY_REQUESTS,
+                "Too many wrong two-factor codes; try again later".to_string(),
+            ));
+        }
+
+        let accepted = match (proof.code.as_deref(), proof.backup_code.as_deref()) {
+            (Some(code), _) => {
+                let secret = self.open_secret(&credential.secret)?;
+                match matching_step(&secret, code, now, self.config.allowed_drift_steps) {
+                    // False when the code's step was already used: a replayed code
+                    Some(step) => self.repository.record_step(customer_id, step).await?,
+                    None => false,
+                }
+            }
+            (None, Some(backup_code)) => {
+                self.repository
+                    .use_backup_code(customer_id, &hash_two_factor_token(&normalize_backup_code(backup_code)))
+                    .await?
+            }
+            (None, None) => return Err(Error::validation("A code or backup code is required")),
+        };
+        if accepted {
+            return Ok(());
+        }
+
+        let lock_until = now + Duration::minutes(self.config.lockout_mins as i64);
+        self.repository
+            .record_failure(customer_id, self.config.max_failed_attempts, lock_until)
+            .await?;
+        Err(Error::unauthorized("Invalid two-factor code"))
+    }
+
+    fn open_secret(&self, stored: &str) -> Result<Vec<u8>> {
+        base32_decode(&self.secrets.open(stored)?)
+            .ok_or_else(|| Error::Other("Stored two-factor secret is malformed".to_string()))
+    }
+}
+
+/// `otpauth://` URI for authenticator apps (usually shown as a QR code)
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer = percent_encode(issuer);
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        percent_encode(account),
+        secret,
+        issuer,
+        CODE_DIGITS,
+        STEP_SECS
+    )
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// HOTP value (RFC 4226) of a counter
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let digest = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = digest.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    (value & 0x7fff_ffff) % 10u32.pow(CODE_DIGITS)
+}
+
+/// TOTP code for a step
+fn totp(secret: &[u8], step: i64) -> String {
+    format!("{:0width$}", hotp(secret, step as u64), width = CODE_DIGITS as usize)
+}
+
+/// The step within `drift` of now whose code this is
+fn matching_step(secret: &[u8], code: &str, now: DateTime<Utc>, drift: u32) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != CODE_DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let current = now.timestamp().div_euclid(STEP_SECS);
+    let drift = drift as i64;
+    (current - drift..=current + drift).find(|step| constant_time_eq(totp(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 8 + 4) / 5);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.trim_end_matches('=').bytes() {
+        let value = BASE32_ALPHABET.iter().position(|&a| a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn generate_challenge_token() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(CHALLENGE_TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    format!("{}{}", TWO_FACTOR_CHALLENGE_PREFIX, secret)
+}
+
+fn generate_backup_codes(count: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut half = || -> String {
+        (0..BACKUP_CODE_HALF)
+            .map(|_| BACKUP_CODE_ALPHABET[rng.gen_range(0..BACKUP_CODE_ALPHABET.len())] as char)
+            .collect()
+    };
+    (0..count)
+        .map(|_| {
+            let first = half();
+            format!("{}-{}", first, half())
+        })
+        .collect()
+}
+
+/// Backup codes as typed: any case, with or without the dash
+fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn hash_backup_codes(codes: &[String]) -> Vec<String> {
+    codes
+        .iter()
+        .map(|code| hash_two_factor_token(&normalize_backup_code(code)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TwoFactorCredential;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockRepository {
+        credential: Mutex<Option<TwoFactorCredential>>,
+        backup_codes: Mutex<Vec<(String, bool)>>,
+        challenges: Mutex<Vec<TwoFactorChallenge>>,
+    }
+
+    #[async_trait]
+    impl TwoFactorRepository for MockRepository {
+        async fn find(&self, _customer_id: Uuid) -> Result<Option<TwoFactorCredential>> {
+            Ok(self.credential.lock().unwrap().clone())
+        }
+
+        async fn start_enrollment(&self, customer_id: Uuid, secret: &str) -> Result<bool> {
+            let mut credential = self.credential.lock().unwrap();
+            if credential.as_ref().is_some_and(|c| c.is_enabled()) {
+                return Ok(false);
+            }
+            *credential = Some(TwoFactorCredential {
+                customer_id,
+                secret: secret.to_string(),
+                confirmed_at: None,
+                last_used_step: None,
+                failed_attempts: 0,
+                locked_until: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            });
+            Ok(true)
+        }
+
+        async fn confirm(&self, _customer_id: Uuid, step: i64, code_hashes: &[String]) -> Result<bool> {
+            let mut credential = self.credential.lock().unwrap();
+            let Some(credential) = credential.as_mut().filter(|c| !c.is_enabled()) else {
+                return Ok(false);
+            };
+            credential.confirmed_at = Some(Utc::now());
+            credential.last_used_step = Some(step);
+            *self.backup_codes.lock().unwrap() = code_hashes.iter().map(|hash| (hash.clone(), false)).collect();
+            Ok(true)
+        }
+
+        async fn record_step(&self, _customer_id: Uuid, step: i64) -> Result<bool> {
+            let mut credential = self.credential.lock().unwrap();
+            let credential = credential.as_mut().unwrap();
+            if credential.last_used_step.is_some_and(|last| last >= step) {
+                return Ok(false);
+            }
+            credential.last_used_step = Some(step);
+            credential.failed_attempts = 0;
+            Ok(true)
+        }
+
+        async fn record_failure(&self, _customer_id: Uuid, max_failures: u32, lock_until: DateTime<Utc>) -> Result<()> {
+            let mut credential = self.credential.lock().unwrap();
+            let credential = credential.as_mut().unwrap();
+            credential.failed_attempts += 1;
+            if credential.failed_attempts >= max_failures as i32 {
+                credential.failed_attempts = 0;
+                credential.locked_until = Some(lock_until);
+            }
+            Ok(())
+        }
+
+        async fn disable(&self, _customer_id: Uuid) -> Result<bool> {
+            self.backup_codes.lock().unwrap().clear();
+            Ok(self.credential.lock().unwrap().take().is_some())
+        }
+
+        async fn replace_backup_codes(&self, _customer_id: Uuid, code_hashes: &[String]) -> Result<()> {
+            *self.backup_codes.lock().unwrap() = code_hashes.iter().map(|hash| (hash.clone(), false)).collect();
+            Ok(())
+        }
+
+        async fn use_backup_code(&self, _customer_id: Uuid, code_hash: &str) -> Result<bool> {
+            let mut codes = self.backup_codes.lock().unwrap();
+            let Some(code) = codes.iter_mut().find(|(hash, used)| hash == code_hash && !used) else {
+                return Ok(false);
+            };
+            code.1 = true;
+            Ok(true)
+        }
+
+        async fn remaining_backup_codes(&self, _customer_id: Uuid) -> Result<i64> {
+            Ok(self.backup_codes.lock().unwrap().iter().filter(|(_, used)| !used).count() as i64)
+        }
+
+        async fn create_challenge(
+            &self,
+            customer_id: Uuid,
+            token_hash: &str,
+            kind: TwoFactorLoginKind,
+            device: &LoginDevice,
+            expires_at: DateTime<Utc>,
+        ) -> Result<TwoFactorChallenge> {
+            let challenge = TwoFactorChallenge {
+                id: Uuid::new_v4(),
+                customer_id,
+                token_hash: token_hash.to_string(),
+                login_kind: kind.as_str().to_string(),
+                user_agent: device.user_agent.clone(),
+                ip_address: device.ip_address.clone(),
+                attempts: 0,
+                expires_at,
+                consumed_at: None,
+                created_at: Utc::now(),
+            };
+            self.challenges.lock().unwrap().push(challenge.clone());
+            Ok(challenge)
+        }
+
+        async fn find_challenge(&self, token_hash: &str) -> Result<Option<TwoFactorChallenge>> {
+            Ok(self.challenges.lock().unwrap().iter().find(|c| c.token_hash == token_hash).cloned())
+        }
+
+        async fn record_challenge_attempt(&self, id: Uuid) -> Result<i32> {
+            let mut challenges = self.challenges.lock().unwrap();
+            let challenge = challenges.iter_mut().find(|c| c.id == id).unwrap();
+            challenge.attempts += 1;
+            Ok(challenge.attempts)
+        }
+
+        async fn consume_challenge(&self, id: Uuid) -> Result<bool> {
+            let mut challenges = self.challenges.lock().unwrap();
+            let challenge = challenges.iter_mut().find(|c| c.id == id && c.consumed_at.is_none());
+            Ok(challenge.map(|c| c.consumed_at = Some(Utc::now())).is_some())
+        }
+
+        async fn purge_challenges(&self, before: DateTime<Utc>) -> Result<u64> {
+            let mut challenges = self.challenges.lock().unwrap();
+            let count = challenges.len();
+            challenges.retain(|c| c.expires_at >= before);
+            Ok((count - challenges.len()) as u64)
+        }
+    }
+
+    fn service() -> TwoFactorService<MockRepository> {
+        TwoFactorService::new(MockRepository::default(), SecretBox::disabled(), TwoFactorConfig::default())
+    }
+
+    fn code_at(setup: &TwoFactorSetup, now: DateTime<Utc>) -> String {
+        totp(&base32_decode(&setup.secret).unwrap(), now.timestamp() / STEP_SECS)
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let secret = b"12345678901234567890";
+        assert_eq!(totp(secret, 59 / STEP_SECS), "287082");
+        assert_eq!(totp(secret, 1111111109 / STEP_SECS), "081804");
+        assert_eq!(totp(secret, 1234567890 / STEP_SECS), "005924");
+    }
+
+    #[test]
+    fn test_base32_round_trip() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("MZXW6YTBOI======").unwrap(), b"foobar");
+        let secret = rand::thread_rng().gen::<[u8; SECRET_BYTES]>();
+        assert_eq!(base32_decode(&base32_encode(&secret)).unwrap(), secret);
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_drift_window() {
+        let secret = b"12345678901234567890";
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let step = now.timestamp() / STEP_SECS;
+        assert_eq!(matching_step(secret, &totp(secret, step - 1), now, 1), Some(step - 1));
+        assert_eq!(matching_step(secret, &totp(secret, step - 2), now, 1), None);
+        assert_eq!(matching_step(secret, "12 34 5", now, 1), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri("R Commerce", "ann@example.com", "ABC");
+        assert_eq!(
+            uri,
+            "otpauth://totp/R%20Commerce:ann%40example.com?secret=ABC&issuer=R%20Commerce&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enroll_and_verify_challenge() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let now = Utc::now();
+        let setup = service.begin_enrollment(customer_id, "ann@example.com").await.unwrap();
+        assert!(!service.is_enabled(customer_id).await.unwrap());
+
+        let backup_codes = service.confirm_enrollment(customer_id, &code_at(&setup, now), now).await.unwrap();
+        assert_eq!(backup_codes.len(), 10);
+        assert!(service.is_enabled(customer_id).await.unwrap());
+        assert!(service.begin_enrollment(customer_id, "ann@example.com").await.is_err());
+
+        // The confirming code can't be used again to log in
+        let issued = service
+            .start_challenge(customer_id, TwoFactorLoginKind::Token, &LoginDevice::default(), now)
+            .await
+            .unwrap();
+        let replay = TwoFactorProof { code: Some(code_at(&setup, now)), backup_code: None };
+        assert!(service.verify_challenge(&issued.token, &replay, now).await.is_err());
+
+        let later = now + Duration::seconds(STEP_SECS);
+        let proof = TwoFactorProof { code: Some(code_at(&setup, later)), backup_code: None };
+        let challenge = service.verify_challenge(&issued.token, &proof, later).await.unwrap();
+        assert_eq!(challenge.customer_id, customer_id);
+        assert!(service.verify_challenge(&issued.token, &proof, later).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backup_codes_are_single_use() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let now = Utc::now();
+        let setup = service.begin_enrollment(customer_id, "ann@example.com").await.unwrap();
+        let codes = service.confirm_enrollment(customer_id, &code_at(&setup, now), now).await.unwrap();
+
+        let proof = TwoFactorProof { code: None, backup_code: Some(codes[0].to_uppercase().replace('-', "")) };
+        let issued = service
+            .start_challenge(customer_id, TwoFactorLoginKind::Cookie, &LoginDevice::default(), now)
+            .await
+            .unwrap();
+        service.verify_challenge(&issued.token, &proof, now).await.unwrap();
+        assert_eq!(service.status(customer_id).await.unwrap().backup_codes_remaining, 9);
+
+        let issued = service
+            .start_challenge(customer_id, TwoFactorLoginKind::Cookie, &LoginDevice::default(), now)
+            .await
+            .unwrap();
+        assert!(service.verify_challenge(&issued.token, &proof, now).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_codes_lock_challenge_and_account() {
+        let service = service();
+        let customer_id = Uuid::new_v4();
+        let now = Utc::now();
+        let setup = service.begin_enrollment(customer_id, "ann@example.com").await.unwrap();
+        service.confirm_enrollment(customer_id, &code_at(&setup, now), now).await.unwrap();
+
+        let wrong = TwoFactorProof { code: Some("000000".to_string()), backup_code: None };
+        let issued = service
+            .start_challenge(customer_id, TwoFactorLoginKind::Token, &LoginDevice::default(), now)
+            .await
+            .unwrap();
+        for _ in 0..5 {
+            assert!(service.verify_challenge(&issued.token, &wrong, now).await.is_err());
+        }
+        // The challenge is used up, even with the right code
+        let later = now + Duration::seconds(STEP_SECS);
+        let right = TwoFactorProof { code: Some(code_at(&setup, later)), backup_code: None };
+        assert!(service.verify_challenge(&issued.token, &right, later).await.is_err());
+
+        for _ in 0..5 {
+            let issued = service
+                .start_challenge(customer_id, TwoFactorLoginKind::Token, &LoginDevice::default(), now)
+                .await
+                .unwrap();
+            let _ = service.verify_challenge(&issued.token, &wrong, now).await;
+        }
+        // Ten wrong codes in a row lock the account's 2FA
+        let issued = service
+            .start_challenge(customer_id, TwoFactorLoginKind::Token, &LoginDevice::default(), later)
+            .await
+            .unwrap();
+        let err = service.verify_challenge(&issued.token, &right, later).await.unwrap_err();
+        assert!(matches!(err, Error::HttpError(status, _) if status == http::StatusCode::TOO_MANY_REQUESTS));
+    }
+}
@@ -0,0 +1,258 @@
+//! Per-endpoint webhook payload transformation
+//!
+//! Webhook endpoints may define a Handlebars payload template and extra
+//! headers, so consumers such as Slack, Zapier or a legacy ERP receive the
+//! shape they expect without writing middleware. Templates see:
+//!
+//! - `event_type` - e.g. `order.created`
+//! - `payload`    - the canonical event payload
+//! - `webhook_id` - the endpoint's ID
+//! - `timestamp`  - delivery time (RFC 3339)
+//!
+//! Values are escaped for the body's content type (JSON by default, XML when
+//! a `Content-Type` header containing "xml" is set). Use `{{{json value}}}`
+//! to embed a value as raw JSON. Header values are templates too.
+//!
+//! The delivery log always stores the canonical payload, so replays can
+//! re-apply the endpoint's current template.
+
+use std::collections::HashMap;
+
+use handlebars::{handlebars_helper, Handlebars, Template};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::ReplayMarker;
+use crate::{Error, Result};
+
+/// Headers set by the delivery subsystem that endpoints may not override
+pub const RESERVED_WEBHOOK_HEADERS: &[&str] = &[
+    "content-length",
+    "host",
+    "x-webhook-signature",
+    "x-webhook-event",
+    "x-webhook-test",
+    "x-webhook-replay",
+    "x-webhook-original-delivery",
+];
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+handlebars_helper!(json: |value: Json| serde_json::to_string(value).unwrap_or_default());
+
+/// An endpoint's payload template and custom headers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookTransform {
+    /// Handlebars template for the request body; the canonical JSON payload is sent when unset
+    pub payload_template: Option<String>,
+    /// Extra request headers (values are Handlebars templates)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// The canonical event payload, sent as is to endpoints without a
+/// payload template (the `payload` templates see)
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookEnvelope {
+    /// Event type, e.g. `order.created`
+    pub event: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Set on deliveries sent with `POST /admin/webhooks/:id/test`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub test: bool,
+    /// The event's data
+    pub data: serde_json::Value,
+    /// Set on redeliveries by a webhook replay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<ReplayMarker>,
+}
+
+impl WebhookEnvelope {
+    pub fn new(event: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            event: event.into(),
+            timestamp: chrono::Utc::now(),
+            test: false,
+            data,
+            replay: None,
+        }
+    }
+
+    /// Payload of a test delivery
+    pub fn test(event: impl Into<String>) -> Self {
+        Self {
+            test: true,
+            ..Self::new(event, serde_json::json!({ "message": "This is a test webhook delivery" }))
+        }
+    }
+}
+
+/// A rendered webhook request body and headers
+#[derive(Debug, Clone)]
+pub struct WebhookRequest {
+    pub body: String,
+    pub content_type: String,
+    /// Custom headers other than Content-Type
+    pub headers: Vec<(String, String)>,
+}
+
+impl WebhookTransform {
+    /// Check that the template compiles and the headers are allowed
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref template) = self.payload_template {
+            Template::compile(template).map_err(|e| Error::validation(format!("Invalid payload template: {}", e)))?;
+        }
+
+        for (name, value) in &self.headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(Error::validation(format!("Invalid header name '{}'", name)));
+            }
+            if RESERVED_WEBHOOK_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                return Err(Error::validation(format!("Header '{}' is set by the webhook sender and cannot be overridden", name)));
+            }
+            Template::compile(value)
+                .map_err(|e| Error::validation(format!("Invalid template for header '{}': {}", name, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Content type of the request body
+    pub fn content_type(&self) -> String {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| JSON_CONTENT_TYPE.to_string())
+    }
+
+    /// Render the request for an event
+    pub fn apply(&self, webhook_id: Uuid, event_type: &str, payload: &serde_json::Value) -> Result<WebhookRequest> {
+        let content_type = self.content_type();
+        let context = serde_json::json!({
+            "event_type": event_type,
+            "payload": payload,
+            "webhook_id": webhook_id,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+
+        let body = match self.payload_template {
+            Some(ref template) => {
+                let body = renderer(Some(&content_type))
+                    .render_template(template, &context)
+                    .map_err(|e| Error::validation(format!("Failed to render payload template: {}", e)))?;
+                if is_json(&content_type) {
+                    serde_json::from_str::<serde_json::Value>(&body)
+                        .map_err(|e| Error::validation(format!("Payload template produced invalid JSON: {}", e)))?;
+                }
+                body
+            }
+            None => payload.to_string(),
+        };
+
+        let header_renderer = renderer(None);
+        let mut headers = Vec::with_capacity(self.headers.len());
+        for (name, template) in &self.headers {
+            if name.eq_ignore_ascii_case("content-type") {
+                continue;
+            }
+            let value = header_renderer
+                .render_template(template, &context)
+                .map_err(|e| Error::validation(format!("Failed to render header '{}': {}", name, e)))?;
+            if HeaderValue::from_str(&value).is_err() {
+                return Err(Error::validation(format!("Header '{}' rendered to an invalid value", name)));
+            }
+            headers.push((name.clone(), value));
+        }
+
+        Ok(WebhookRequest { body, content_type, headers })
+    }
+}
+
+fn is_json(content_type: &str) -> bool {
+    content_type.to_ascii_lowercase().contains("json")
+}
+
+/// Handlebars registry escaping values for the given body content type
+/// (no escaping for headers)
+fn renderer(content_type: Option<&str>) -> Handlebars<'static> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("json", Box::new(json));
+    match content_type {
+        Some(ct) if is_json(ct) => handlebars.register_escape_fn(json_escape),
+        Some(ct) if ct.to_ascii_lowercase().contains("xml") => handlebars.register_escape_fn(handlebars::html_escape),
+        _ => handlebars.register_escape_fn(handlebars::no_escape),
+    }
+    handlebars
+}
+
+/// Escape a string for use inside a JSON string literal
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_created() -> serde_json::Value {
+        serde_json::json!({
+            "event": "order.created",
+            "data": { "order_number": "1001", "email": "ann \"the buyer\"@example.com", "items": [1, 2] }
+        })
+    }
+
+    #[test]
+    fn test_without_template_sends_canonical_payload() {
+        let request = WebhookTransform::default().apply(Uuid::new_v4(), "order.created", &order_created()).unwrap();
+
+        assert_eq!(request.content_type, "application/json");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&request.body).unwrap(), order_created());
+    }
+
+    #[test]
+    fn test_slack_template_escapes_and_embeds_json() {
+        let transform = WebhookTransform {
+            payload_template: Some(
+                r#"{"text": "Order {{payload.data.order_number}} from {{payload.data.email}}", "items": {{{json payload.data.items}}}}"#.to_string(),
+            ),
+            headers: HashMap::from([("X-Source".to_string(), "rcommerce/{{event_type}}".to_string())]),
+        };
+        transform.validate().unwrap();
+
+        let request = transform.apply(Uuid::new_v4(), "order.created", &order_created()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["text"], "Order 1001 from ann \"the buyer\"@example.com");
+        assert_eq!(body["items"], serde_json::json!([1, 2]));
+        assert_eq!(request.headers, vec![("X-Source".to_string(), "rcommerce/order.created".to_string())]);
+    }
+
+    #[test]
+    fn test_xml_template() {
+        let transform = WebhookTransform {
+            payload_template: Some("<order number=\"{{payload.data.order_number}}\"><email>{{payload.data.email}}</email></order>".to_string()),
+            headers: HashMap::from([("Content-Type".to_string(), "application/xml".to_string())]),
+        };
+
+        let request = transform.apply(Uuid::new_v4(), "order.created", &order_created()).unwrap();
+        assert_eq!(request.content_type, "application/xml");
+        assert!(request.body.contains("ann &quot;the buyer&quot;@example.com"));
+        assert!(request.headers.is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_templates_and_reserved_headers() {
+        let broken = WebhookTransform { payload_template: Some("{{#if}}".to_string()), ..Default::default() };
+        assert!(broken.validate().is_err());
+
+        let reserved = WebhookTransform {
+            headers: HashMap::from([("X-Webhook-Signature".to_string(), "forged".to_string())]),
+            ..Default::default()
+        };
+        assert!(reserved.validate().is_err());
+
+        let invalid_json = WebhookTransform { payload_template: Some("{\"a\": }".to_string()), ..Default::default() };
+        assert!(invalid_json.apply(Uuid::new_v4(), "order.created", &order_created()).is_err());
+    }
+}
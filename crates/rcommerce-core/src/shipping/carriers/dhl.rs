@@ -258,6 +258,7 @@ impl ShippingProvider for DhlProvider {
                 }],
                 is_customs_declarable: customs_info.is_some(),
                 description: customs_info.map(|c| c.contents_description.clone()).unwrap_or_default(),
+                incoterm: customs_info.and_then(|c| c.incoterm).map(|incoterm| incoterm.to_string()),
             },
         };
         
@@ -739,6 +740,8 @@ struct ShipmentContent {
     #[serde(rename = "isCustomsDeclarable")]
     is_customs_declarable: bool,
     description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incoterm: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
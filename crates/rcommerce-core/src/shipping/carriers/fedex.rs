@@ -10,7 +10,7 @@ use crate::common::Address;
 use crate::shipping::{
     ShippingProvider, ShippingRate, Shipment, TrackingInfo, TrackingStatus, TrackingEvent,
     Package, RateOptions, AddressValidation, ShippingService, ServiceFeature,
-    CustomsInfo,
+    CustomsInfo, DeliveryDetails,
 };
 use crate::Error;
 
@@ -113,6 +113,7 @@ impl FedExProvider {
     fn service_name(&self, code: &str) -> String {
         match code {
             "FEDEX_GROUND" => "FedEx Ground",
+            "GROUND_HOME_DELIVERY" => "FedEx Home Delivery",
             "FEDEX_EXPRESS_SAVER" => "FedEx Express Saver",
             "FEDEX_2_DAY" => "FedEx 2Day",
             "FEDEX_2_DAY_AM" => "FedEx 2Day A.M.",
@@ -300,6 +301,26 @@ impl ShippingProvider for FedExProvider {
         package: &Package,
         service_code: &str,
         customs_info: Option<&CustomsInfo>,
+    ) -> Result<Shipment> {
+        self.create_shipment_with_delivery(
+            from_address,
+            to_address,
+            package,
+            service_code,
+            customs_info,
+            &DeliveryDetails::default(),
+        )
+        .await
+    }
+    
+    async fn create_shipment_with_delivery(
+        &self,
+        from_address: &Address,
+        to_address: &Address,
+        package: &Package,
+        service_code: &str,
+        customs_info: Option<&CustomsInfo>,
+        delivery: &DeliveryDetails,
     ) -> Result<Shipment> {
         let mut provider = Self {
             client: self.client.clone(),
@@ -329,6 +350,7 @@ impl ShippingProvider for FedExProvider {
                         state_or_province_code: from_address.state.clone(),
                         street_lines: vec![from_address.address1.clone()],
                     },
+                    delivery_instructions: None,
                 },
                 recipient: FedExPartyDetail {
                     contact: FedExContact {
@@ -342,6 +364,7 @@ impl ShippingProvider for FedExProvider {
                         state_or_province_code: to_address.state.clone(),
                         street_lines: vec![to_address.address1.clone()],
                     },
+                    delivery_instructions: delivery.instructions.clone(),
                 },
                 pickup_type: "DROPOFF_AT_FEDEX_LOCATION".to_string(),
                 service_type: service_code.to_string(),
@@ -362,8 +385,12 @@ impl ShippingProvider for FedExProvider {
                     },
                 }],
                 customs_clearance_detail: customs_info.map(|c| FedExCustomsDetail {
+                    // Duties are billed to the store only for DDP orders
                     duties_payment: FedExPayment {
-                        payment_type: "SENDER".to_string(),
+                        payment_type: match c.incoterm {
+                            Some(crate::tax::Incoterm::Dap) => "RECIPIENT".to_string(),
+                            _ => "SENDER".to_string(),
+                        },
                     },
                     commodities: c.customs_items.iter().map(|item| FedExCommodity {
                         description: item.description.clone(),
@@ -380,6 +407,17 @@ impl ShippingProvider for FedExProvider {
                     image_type: "PDF".to_string(),
                     label_stock_type: "PAPER_4X6".to_string(),
                 },
+                // Home Delivery books a date through its Date Certain premium
+                shipment_special_services: delivery.window.as_ref().map(|window| FedExShipmentSpecialServices {
+                    special_service_types: vec!["HOME_DELIVERY_PREMIUM".to_string()],
+                    home_delivery_premium_detail: FedExHomeDeliveryPremiumDetail {
+                        home_delivery_premium_type: "DATE_CERTAIN".to_string(),
+                        delivery_date: window.date.to_string(),
+                        phone_number: FedExPhoneNumber {
+                            number: to_address.phone.clone().unwrap_or_default(),
+                        },
+                    },
+                }),
             },
         };
         
@@ -394,7 +432,7 @@ impl ShippingProvider for FedExProvider {
             .send()
             .await;
         
-        match response {
+        let mut shipment = match response {
             Ok(resp) => {
                 if resp.status().is_success() {
                     let ship_response: FedExShipResponse = resp
@@ -449,7 +487,9 @@ impl ShippingProvider for FedExProvider {
                 tracing::warn!("FedEx shipment API request failed: {}. Falling back to mock shipment.", e);
                 self.create_mock_shipment(from_address, to_address, package, service_code, customs_info)
             }
-        }
+        }?;
+        delivery.write_metadata(&mut shipment.metadata);
+        Ok(shipment)
     }
     
     async fn track_shipment(&self, tracking_number: &str) -> Result<TrackingInfo> {
@@ -612,6 +652,7 @@ impl ShippingProvider for FedExProvider {
     fn get_services(&self) -> Vec<ShippingService> {
         vec![
             ShippingService { code: "FEDEX_GROUND".to_string(), name: "FedEx Ground".to_string(), carrier: self.name().to_string(), domestic: true, international: false, transit_time_days: Some((1, 5)), features: vec![ServiceFeature::Tracking, ServiceFeature::Ground] },
+            ShippingService { code: "GROUND_HOME_DELIVERY".to_string(), name: "FedEx Home Delivery".to_string(), carrier: self.name().to_string(), domestic: true, international: false, transit_time_days: Some((1, 5)), features: vec![ServiceFeature::Tracking, ServiceFeature::Ground, ServiceFeature::ScheduledDelivery, ServiceFeature::DeliveryInstructions] },
             ShippingService { code: "FEDEX_2_DAY".to_string(), name: "FedEx 2Day".to_string(), carrier: self.name().to_string(), domestic: true, international: false, transit_time_days: Some((2, 2)), features: vec![ServiceFeature::Tracking, ServiceFeature::Express] },
             ShippingService { code: "PRIORITY_OVERNIGHT".to_string(), name: "FedEx Priority Overnight".to_string(), carrier: self.name().to_string(), domestic: true, international: false, transit_time_days: Some((1, 1)), features: vec![ServiceFeature::Tracking, ServiceFeature::Express] },
             ShippingService { code: "INTERNATIONAL_PRIORITY".to_string(), name: "FedEx International Priority".to_string(), carrier: self.name().to_string(), domestic: false, international: true, transit_time_days: Some((1, 3)), features: vec![ServiceFeature::Tracking, ServiceFeature::Express] },
@@ -853,12 +894,39 @@ struct FedExRequestedShipmentDetail {
     customs_clearance_detail: Option<FedExCustomsDetail>,
     #[serde(rename = "labelSpecification")]
     label_specification: FedExLabelSpec,
+    #[serde(rename = "shipmentSpecialServices", skip_serializing_if = "Option::is_none")]
+    shipment_special_services: Option<FedExShipmentSpecialServices>,
 }
 
 #[derive(Debug, Serialize)]
 struct FedExPartyDetail {
     contact: FedExContact,
     address: FedExAddress,
+    #[serde(rename = "deliveryInstructions", skip_serializing_if = "Option::is_none")]
+    delivery_instructions: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FedExShipmentSpecialServices {
+    #[serde(rename = "specialServiceTypes")]
+    special_service_types: Vec<String>,
+    #[serde(rename = "homeDeliveryPremiumDetail")]
+    home_delivery_premium_detail: FedExHomeDeliveryPremiumDetail,
+}
+
+#[derive(Debug, Serialize)]
+struct FedExHomeDeliveryPremiumDetail {
+    #[serde(rename = "homedeliveryPremiumType")]
+    home_delivery_premium_type: String,
+    #[serde(rename = "deliveryDate")]
+    delivery_date: String,
+    #[serde(rename = "phoneNumber")]
+    phone_number: FedExPhoneNumber,
+}
+
+#[derive(Debug, Serialize)]
+struct FedExPhoneNumber {
+    number: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,335 @@
+//! Delivery instructions and scheduled delivery windows
+//!
+//! Customers can leave instructions for the driver and, where the carrier
+//! service supports it, book a delivery date and time window. Bookings are
+//! checked against the `[delivery]` dispatch cutoff, lead time, weekend
+//! rules and blackout dates.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use super::{ServiceFeature, ShippingProviderFactory};
+use crate::config::DeliveryConfig;
+use crate::{Error, Result};
+
+/// A bookable time of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSlot {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeSlot {
+    /// Parse "HH:MM-HH:MM"
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.split_once('-')?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+        (start < end).then_some(Self { start, end })
+    }
+}
+
+/// A requested delivery date, optionally within a time window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeliveryWindow {
+    pub date: NaiveDate,
+    pub start: Option<NaiveTime>,
+    pub end: Option<NaiveTime>,
+}
+
+impl DeliveryWindow {
+    /// E.g. "2026-10-20 08:00-12:00"
+    pub fn describe(&self) -> String {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => format!("{} {}-{}", self.date, start.format("%H:%M"), end.format("%H:%M")),
+            _ => self.date.to_string(),
+        }
+    }
+}
+
+/// Delivery instructions and window for a shipment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeliveryDetails {
+    /// Free-text instructions for the driver, e.g. "Leave with reception"
+    pub instructions: Option<String>,
+    pub window: Option<DeliveryWindow>,
+}
+
+impl DeliveryDetails {
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_none() && self.window.is_none()
+    }
+
+    /// Record on a shipment for carriers whose APIs do not take them
+    pub fn write_metadata(&self, metadata: &mut HashMap<String, String>) {
+        if let Some(ref instructions) = self.instructions {
+            metadata.insert("delivery_instructions".to_string(), instructions.clone());
+        }
+        if let Some(ref window) = self.window {
+            metadata.insert("delivery_window".to_string(), window.describe());
+        }
+    }
+}
+
+/// Bookable delivery dates and windows for a carrier service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliverySchedule {
+    /// Whether the service can be booked for a date (instructions are always accepted)
+    pub scheduled_delivery: bool,
+    /// When an order placed now is dispatched
+    pub dispatch_date: NaiveDate,
+    pub dates: Vec<NaiveDate>,
+    pub windows: Vec<TimeSlot>,
+    pub max_instructions_length: usize,
+}
+
+/// Whether a carrier service can be booked for a delivery window
+pub fn supports_scheduled_delivery(factory: &ShippingProviderFactory, provider_id: &str, service_code: &str) -> bool {
+    factory
+        .get(provider_id)
+        .map(|provider| provider.supports_feature(service_code, ServiceFeature::ScheduledDelivery))
+        .unwrap_or(false)
+}
+
+/// Checks delivery bookings against the store's dispatch calendar
+#[derive(Debug, Clone)]
+pub struct DeliveryScheduler {
+    cutoff: NaiveTime,
+    offset: FixedOffset,
+    min_lead_days: u32,
+    max_days_ahead: u32,
+    ship_on_weekends: bool,
+    deliver_on_weekends: bool,
+    windows: Vec<TimeSlot>,
+    blackout_dates: BTreeSet<NaiveDate>,
+    max_instructions_length: usize,
+}
+
+impl DeliveryScheduler {
+    pub fn from_config(config: &DeliveryConfig) -> Result<Self> {
+        let cutoff = NaiveTime::parse_from_str(&config.cutoff_time, "%H:%M").map_err(|_| {
+            Error::Config(format!("delivery.cutoff_time '{}' is not HH:MM", config.cutoff_time))
+        })?;
+        let offset = FixedOffset::east_opt(config.utc_offset_minutes * 60).ok_or_else(|| {
+            Error::Config(format!("delivery.utc_offset_minutes {} is out of range", config.utc_offset_minutes))
+        })?;
+        let windows = config
+            .windows
+            .iter()
+            .map(|window| {
+                TimeSlot::parse(window).ok_or_else(|| {
+                    Error::Config(format!("delivery.windows entry '{}' is not HH:MM-HH:MM", window))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if config.max_days_ahead < config.min_lead_days {
+            return Err(Error::Config("delivery.max_days_ahead must be at least min_lead_days".to_string()));
+        }
+
+        Ok(Self {
+            cutoff,
+            offset,
+            min_lead_days: config.min_lead_days,
+            max_days_ahead: config.max_days_ahead,
+            ship_on_weekends: config.ship_on_weekends,
+            deliver_on_weekends: config.deliver_on_weekends,
+            windows,
+            blackout_dates: config.blackout_dates.iter().copied().collect(),
+            max_instructions_length: config.max_instructions_length,
+        })
+    }
+
+    fn is_weekend(date: NaiveDate) -> bool {
+        matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+
+    fn ships_on(&self, date: NaiveDate) -> bool {
+        (self.ship_on_weekends || !Self::is_weekend(date)) && !self.blackout_dates.contains(&date)
+    }
+
+    fn delivers_on(&self, date: NaiveDate) -> bool {
+        (self.deliver_on_weekends || !Self::is_weekend(date)) && !self.blackout_dates.contains(&date)
+    }
+
+    /// Store-local date an order placed at `now` is dispatched
+    pub fn dispatch_date(&self, now: DateTime<Utc>) -> NaiveDate {
+        let local = now.with_timezone(&self.offset);
+        let mut date = local.date_naive();
+        if local.time() >= self.cutoff {
+            date += Duration::days(1);
+        }
+        // Blackout dates are finite, so this ends
+        while !self.ships_on(date) {
+            date += Duration::days(1);
+        }
+        date
+    }
+
+    /// Delivery dates bookable for an order placed at `now`
+    pub fn available_dates(&self, now: DateTime<Utc>) -> Vec<NaiveDate> {
+        let today = now.with_timezone(&self.offset).date_naive();
+        let earliest = self.dispatch_date(now) + Duration::days(self.min_lead_days as i64);
+        let latest = today + Duration::days(self.max_days_ahead as i64);
+        earliest
+            .iter_days()
+            .take_while(|date| *date <= latest)
+            .filter(|date| self.delivers_on(*date))
+            .collect()
+    }
+
+    /// Dates and windows offered for a carrier service
+    pub fn schedule(&self, now: DateTime<Utc>, scheduled_delivery: bool) -> DeliverySchedule {
+        DeliverySchedule {
+            scheduled_delivery,
+            dispatch_date: self.dispatch_date(now),
+            dates: if scheduled_delivery { self.available_dates(now) } else { Vec::new() },
+            windows: if scheduled_delivery { self.windows.clone() } else { Vec::new() },
+            max_instructions_length: self.max_instructions_length,
+        }
+    }
+
+    /// Validate a customer's delivery request, returning it tidied
+    pub fn validate(
+        &self,
+        now: DateTime<Utc>,
+        details: DeliveryDetails,
+        scheduled_delivery: bool,
+    ) -> Result<DeliveryDetails> {
+        let instructions = details
+            .instructions
+            .map(|instructions| instructions.trim().to_string())
+            .filter(|instructions| !instructions.is_empty());
+        if instructions
+            .as_ref()
+            .is_some_and(|instructions| instructions.chars().count() > self.max_instructions_length)
+        {
+            return Err(Error::validation(format!(
+                "Delivery instructions are longer than {} characters",
+                self.max_instructions_length
+            )));
+        }
+
+        let Some(window) = details.window else {
+            return Ok(DeliveryDetails { instructions, window: None });
+        };
+        if !scheduled_delivery {
+            return Err(Error::validation("The selected shipping service does not offer scheduled delivery"));
+        }
+        if !self.available_dates(now).contains(&window.date) {
+            return Err(Error::validation(format!(
+                "Delivery on {} is not available (dispatch {}, no weekends or blackout dates)",
+                window.date,
+                self.dispatch_date(now)
+            )));
+        }
+        match (window.start, window.end) {
+            (None, None) => {}
+            (Some(start), Some(end)) => {
+                if !self.windows.contains(&TimeSlot { start, end }) {
+                    return Err(Error::validation(format!(
+                        "{}-{} is not a delivery window",
+                        start.format("%H:%M"),
+                        end.format("%H:%M")
+                    )));
+                }
+            }
+            _ => return Err(Error::validation("A delivery window needs both a start and an end time")),
+        }
+
+        Ok(DeliveryDetails {
+            instructions,
+            window: Some(window),
+        })
+    }
+}
+
+impl Default for DeliveryScheduler {
+    fn default() -> Self {
+        Self::from_config(&DeliveryConfig::default()).expect("default delivery config is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn scheduler() -> DeliveryScheduler {
+        DeliveryScheduler::from_config(&DeliveryConfig {
+            blackout_dates: vec![NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()],
+            ..DeliveryConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn test_dispatch_date_respects_cutoff_and_weekends() {
+        let scheduler = scheduler();
+        // Thursday 2026-10-15, before and after the 14:00 cutoff
+        assert_eq!(scheduler.dispatch_date(Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap()), date(15));
+        assert_eq!(scheduler.dispatch_date(Utc.with_ymd_and_hms(2026, 10, 15, 15, 0, 0).unwrap()), date(16));
+        // Friday after cutoff ships Monday
+        assert_eq!(scheduler.dispatch_date(Utc.with_ymd_and_hms(2026, 10, 16, 15, 0, 0).unwrap()), date(19));
+    }
+
+    #[test]
+    fn test_available_dates_skip_weekends_and_blackouts() {
+        let dates = scheduler().available_dates(Utc.with_ymd_and_hms(2026, 10, 16, 15, 0, 0).unwrap());
+        // Dispatch Monday 19th, earliest delivery Tuesday 20th which is blacked out
+        assert_eq!(dates.first(), Some(&date(21)));
+        assert!(!dates.contains(&date(24)));
+        assert_eq!(dates.last(), Some(&NaiveDate::from_ymd_opt(2026, 11, 6).unwrap()));
+    }
+
+    #[test]
+    fn test_validate_delivery_request() {
+        let scheduler = scheduler();
+        let now = Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap();
+        let slot = |start: &str, end: &str| DeliveryWindow {
+            date: date(16),
+            start: Some(NaiveTime::parse_from_str(start, "%H:%M").unwrap()),
+            end: Some(NaiveTime::parse_from_str(end, "%H:%M").unwrap()),
+        };
+
+        let booked = scheduler
+            .validate(
+                now,
+                DeliveryDetails {
+                    instructions: Some("  Ring twice ".to_string()),
+                    window: Some(slot("08:00", "12:00")),
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(booked.instructions.as_deref(), Some("Ring twice"));
+        assert_eq!(booked.window.unwrap().describe(), "2026-10-16 08:00-12:00");
+
+        let odd_window = DeliveryDetails { instructions: None, window: Some(slot("09:00", "10:00")) };
+        assert!(scheduler.validate(now, odd_window, true).is_err());
+
+        let unsupported = DeliveryDetails { instructions: None, window: Some(slot("08:00", "12:00")) };
+        assert!(scheduler.validate(now, unsupported, false).is_err());
+
+        let too_soon = DeliveryDetails {
+            instructions: None,
+            window: Some(DeliveryWindow { date: date(15), start: None, end: None }),
+        };
+        assert!(scheduler.validate(now, too_soon, true).is_err());
+
+        let instructions_only = DeliveryDetails { instructions: Some("Side door".to_string()), window: None };
+        assert!(scheduler.validate(now, instructions_only, false).is_ok());
+    }
+
+    #[test]
+    fn test_time_slot_parse() {
+        assert!(TimeSlot::parse("08:00-12:00").is_some());
+        assert!(TimeSlot::parse("12:00-08:00").is_none());
+        assert!(TimeSlot::parse("noon").is_none());
+    }
+}
@@ -4,7 +4,8 @@
 //! - Weight-based and volumetric weight calculations
 //! - Real-time rate calculation from multiple carriers
 //! - Shipping label generation
-//! - Shipment tracking
+//! - Shipment tracking, pushed by aggregator webhooks or polled (see [`tracking`])
+//! - Free in-store pickup at inventory locations (see [`pickup`])
 //! - Multi-carrier support (DHL, FedEx, UPS, USPS)
 //! - Third-party aggregator support (EasyPost, ShipStation)
 
@@ -24,11 +25,22 @@ pub mod providers;
 pub mod zones;
 pub mod rules;
 pub mod packaging;
+pub mod delivery;
+pub mod tracking;
+pub mod pickup;
 
 pub use calculation::{ShippingCalculator, VolumetricWeightCalculator, WeightConverter};
 pub use packaging::{Package, PackageType, PackagingCalculator};
 pub use zones::{ShippingZone, ZoneRate, ZoneCalculator};
 pub use rules::{ShippingRule, ShippingRuleEngine, RuleCondition, RuleAction};
+pub use delivery::{
+    supports_scheduled_delivery, DeliveryDetails, DeliverySchedule, DeliveryScheduler, DeliveryWindow, TimeSlot,
+};
+pub use pickup::{
+    is_pickup_rate, pickup_rate, PickupAvailability, PickupLine, PickupLocation, PickupService, PickupShipment,
+    UpdatePickupLocation, PICKUP_PROVIDER,
+};
+pub use tracking::{TrackingPollJob, TrackingPollReport, TrackingService, TrackingWebhookResult, WebhookRegistration};
 
 /// Core shipping provider trait
 #[async_trait]
@@ -61,9 +73,45 @@ pub trait ShippingProvider: Send + Sync + 'static {
         customs_info: Option<&CustomsInfo>,
     ) -> Result<Shipment>;
     
+    /// Create a shipment with the customer's delivery instructions and window
+    ///
+    /// Carriers whose APIs take these override this; by default they are
+    /// only recorded in the shipment metadata for the label and packing slip.
+    async fn create_shipment_with_delivery(
+        &self,
+        from_address: &Address,
+        to_address: &Address,
+        package: &Package,
+        service_code: &str,
+        customs_info: Option<&CustomsInfo>,
+        delivery: &DeliveryDetails,
+    ) -> Result<Shipment> {
+        let mut shipment = self
+            .create_shipment(from_address, to_address, package, service_code, customs_info)
+            .await?;
+        delivery.write_metadata(&mut shipment.metadata);
+        Ok(shipment)
+    }
+    
     /// Track a shipment
     async fn track_shipment(&self, tracking_number: &str) -> Result<TrackingInfo>;
     
+    /// Whether the provider pushes tracking updates to a registered
+    /// webhook; shipments of other providers are polled
+    fn has_tracking_webhooks(&self) -> bool {
+        false
+    }
+    
+    /// Register `url` to receive the provider's tracking updates
+    async fn register_tracking_webhook(&self, _url: &str) -> Result<()> {
+        Err(Error::shipping(format!("{} does not send tracking webhooks", self.name())))
+    }
+    
+    /// Tracking updates in a webhook the provider sent
+    async fn parse_tracking_webhook(&self, _body: &[u8]) -> Result<Vec<TrackingInfo>> {
+        Err(Error::shipping(format!("{} does not send tracking webhooks", self.name())))
+    }
+    
     /// Cancel a shipment (if possible)
     async fn cancel_shipment(&self, shipment_id: &str) -> Result<bool>;
     
@@ -73,6 +121,13 @@ pub trait ShippingProvider: Send + Sync + 'static {
     /// Get available services for this provider
     fn get_services(&self) -> Vec<ShippingService>;
     
+    /// Whether a service offers a feature
+    fn supports_feature(&self, service_code: &str, feature: ServiceFeature) -> bool {
+        self.get_services()
+            .iter()
+            .any(|service| service.code == service_code && service.features.contains(&feature))
+    }
+    
     /// Estimate delivery date
     async fn estimate_delivery(
         &self,
@@ -265,6 +320,21 @@ pub enum TrackingStatus {
 }
 
 impl TrackingStatus {
+    /// Name stored with shipments
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackingStatus::PreTransit => "pre_transit",
+            TrackingStatus::InTransit => "in_transit",
+            TrackingStatus::OutForDelivery => "out_for_delivery",
+            TrackingStatus::Delivered => "delivered",
+            TrackingStatus::AvailableForPickup => "available_for_pickup",
+            TrackingStatus::ReturnToSender => "return_to_sender",
+            TrackingStatus::Failure => "failure",
+            TrackingStatus::Cancelled => "cancelled",
+            TrackingStatus::Exception => "exception",
+        }
+    }
+    
     pub fn description(&self) -> &'static str {
         match self {
             TrackingStatus::PreTransit => "Pre-transit",
@@ -303,6 +373,9 @@ pub struct CustomsInfo {
     pub customs_items: Vec<CustomsItem>,
     pub declaration_value: Decimal,
     pub declaration_currency: String,
+    /// Who pays duties and import taxes; None leaves it to the carrier account
+    #[serde(default)]
+    pub incoterm: Option<crate::tax::Incoterm>,
 }
 
 /// Customs item
@@ -405,72 +478,74 @@ pub enum ServiceFeature {
     DeliveryConfirmation,
     Express,
     Ground,
+    /// Delivery can be booked for a date and time window
+    ScheduledDelivery,
+    /// Driver instructions are passed to the carrier
+    DeliveryInstructions,
 }
 
 /// Shipping provider factory
+///
+/// Holds the store-wide carrier accounts and, for inventory locations with
+/// their own (`[[shipping.locations]]`), each location's accounts and
+/// origin, so parcels ship on the account of the location they leave from.
 pub struct ShippingProviderFactory {
     providers: HashMap<String, Box<dyn ShippingProvider>>,
+    origin: Option<crate::config::ShippingOriginConfig>,
+    locations: HashMap<String, LocationCarriers>,
+}
+
+/// Carrier accounts and origin of one inventory location
+struct LocationCarriers {
+    providers: HashMap<String, Box<dyn ShippingProvider>>,
+    origin: Option<crate::config::ShippingOriginConfig>,
 }
 
 impl ShippingProviderFactory {
     pub fn new() -> Self {
         Self {
             providers: HashMap::new(),
+            origin: None,
+            locations: HashMap::new(),
         }
     }
     
     /// Create a factory from configuration
     pub fn from_config(config: &crate::config::ShippingConfig) -> Self {
         let mut factory = Self::new();
+        factory.providers = carrier_providers(&config.dhl, &config.fedex, &config.ups, &config.usps, config.test_mode);
+        factory.origin = config.origin.clone();
         
-        // Register DHL if configured
-        if config.dhl.enabled {
-            if let (Some(api_key), Some(api_secret), Some(account_number)) = 
-                (&config.dhl.api_key, &config.dhl.api_secret, &config.dhl.account_number) {
-                let provider = crate::shipping::carriers::DhlProvider::new(
-                    api_key.clone(),
-                    api_secret.clone(),
-                    account_number.clone(),
-                ).with_test_mode(config.dhl.sandbox || config.test_mode);
-                factory.register(Box::new(provider));
+        // Aggregators are store-wide accounts
+        if config.easypost.enabled {
+            if let Some(api_key) = &config.easypost.api_key {
+                factory.register(Box::new(
+                    providers::EasyPostProvider::new(api_key.clone()).with_test_mode(config.test_mode),
+                ));
             }
         }
-        
-        // Register FedEx if configured
-        if config.fedex.enabled {
-            if let (Some(api_key), Some(api_secret), Some(account_number)) = 
-                (&config.fedex.api_key, &config.fedex.api_secret, &config.fedex.account_number) {
-                let provider = crate::shipping::carriers::FedExProvider::new(
-                    api_key.clone(),
-                    api_secret.clone(),
-                    account_number.clone(),
-                ).with_test_mode(config.fedex.sandbox || config.test_mode);
-                factory.register(Box::new(provider));
+        if config.shipstation.enabled {
+            if let (Some(api_key), Some(api_secret)) = (&config.shipstation.api_key, &config.shipstation.api_secret) {
+                factory.register(Box::new(providers::ShipStationProvider::new(api_key.clone(), api_secret.clone())));
             }
         }
         
-        // Register UPS if configured
-        if config.ups.enabled {
-            if let (Some(api_key), Some(username), Some(password), Some(account_number)) = 
-                (&config.ups.api_key, &config.ups.username, &config.ups.password, &config.ups.account_number) {
-                let provider = crate::shipping::carriers::UpsProvider::new(
-                    api_key.clone(),
-                    username.clone(),
-                    password.clone(),
-                    account_number.clone(),
-                ).with_test_mode(config.ups.sandbox || config.test_mode);
-                factory.register(Box::new(provider));
-            }
-        }
-        
-        // Register USPS if configured
-        if config.usps.enabled {
-            if let Some(api_key) = &config.usps.api_key {
-                let provider = crate::shipping::carriers::UspsProvider::new(
-                    api_key.clone(),
-                ).with_test_mode(config.usps.sandbox || config.test_mode);
-                factory.register(Box::new(provider));
-            }
+        // Locations use their own accounts, and the store's for other carriers
+        for location in &config.locations {
+            let providers = carrier_providers(
+                location.dhl.as_ref().unwrap_or(&config.dhl),
+                location.fedex.as_ref().unwrap_or(&config.fedex),
+                location.ups.as_ref().unwrap_or(&config.ups),
+                location.usps.as_ref().unwrap_or(&config.usps),
+                config.test_mode,
+            );
+            factory.locations.insert(
+                location.location.to_ascii_lowercase(),
+                LocationCarriers {
+                    providers,
+                    origin: location.origin.clone().or_else(|| config.origin.clone()),
+                },
+            );
         }
         
         factory
@@ -507,6 +582,107 @@ impl ShippingProviderFactory {
     pub fn has(&self, id: &str) -> bool {
         self.providers.contains_key(id)
     }
+    
+    fn location_providers(&self, location: Option<&str>) -> &HashMap<String, Box<dyn ShippingProvider>> {
+        location
+            .and_then(|code| self.locations.get(&code.to_ascii_lowercase()))
+            .map_or(&self.providers, |carriers| &carriers.providers)
+    }
+    
+    /// Get a provider by ID on the account of the inventory location
+    /// (by code) a parcel ships from; locations without their own
+    /// accounts, and None, use the store-wide accounts
+    pub fn get_for_location(&self, location: Option<&str>, id: &str) -> Result<&dyn ShippingProvider> {
+        self.location_providers(location)
+            .get(id)
+            .map(|p| p.as_ref())
+            .ok_or_else(|| match location {
+                Some(code) => Error::not_found(format!("Shipping provider '{}' is not configured for location {}", id, code)),
+                None => Error::not_found(format!("Shipping provider '{}' not found", id)),
+            })
+    }
+    
+    /// Available providers for parcels from an inventory location
+    pub fn get_available_for_location(&self, location: Option<&str>) -> Vec<&dyn ShippingProvider> {
+        self.location_providers(location)
+            .values()
+            .filter(|p| p.is_available())
+            .map(|p| p.as_ref())
+            .collect()
+    }
+    
+    /// Where parcels from an inventory location ship from
+    pub fn origin(&self, location: Option<&str>) -> Option<&crate::config::ShippingOriginConfig> {
+        location
+            .and_then(|code| self.locations.get(&code.to_ascii_lowercase()))
+            .map_or(self.origin.as_ref(), |carriers| carriers.origin.as_ref())
+    }
+}
+
+/// Providers for the configured carrier accounts
+fn carrier_providers(
+    dhl: &crate::config::DhlConfig,
+    fedex: &crate::config::FedExConfig,
+    ups: &crate::config::UpsConfig,
+    usps: &crate::config::UspsConfig,
+    test_mode: bool,
+) -> HashMap<String, Box<dyn ShippingProvider>> {
+    let mut providers: Vec<Box<dyn ShippingProvider>> = Vec::new();
+    
+    // Register DHL if configured
+    if dhl.enabled {
+        if let (Some(api_key), Some(api_secret), Some(account_number)) = 
+            (&dhl.api_key, &dhl.api_secret, &dhl.account_number) {
+            let provider = crate::shipping::carriers::DhlProvider::new(
+                api_key.clone(),
+                api_secret.clone(),
+                account_number.clone(),
+            ).with_test_mode(dhl.sandbox || test_mode);
+            providers.push(Box::new(provider));
+        }
+    }
+    
+    // Register FedEx if configured
+    if fedex.enabled {
+        if let (Some(api_key), Some(api_secret), Some(account_number)) = 
+            (&fedex.api_key, &fedex.api_secret, &fedex.account_number) {
+            let provider = crate::shipping::carriers::FedExProvider::new(
+                api_key.clone(),
+                api_secret.clone(),
+                account_number.clone(),
+            ).with_test_mode(fedex.sandbox || test_mode);
+            providers.push(Box::new(provider));
+        }
+    }
+    
+    // Register UPS if configured
+    if ups.enabled {
+        if let (Some(api_key), Some(username), Some(password), Some(account_number)) = 
+            (&ups.api_key, &ups.username, &ups.password, &ups.account_number) {
+            let provider = crate::shipping::carriers::UpsProvider::new(
+                api_key.clone(),
+                username.clone(),
+                password.clone(),
+                account_number.clone(),
+            ).with_test_mode(ups.sandbox || test_mode);
+            providers.push(Box::new(provider));
+        }
+    }
+    
+    // Register USPS if configured
+    if usps.enabled {
+        if let Some(api_key) = &usps.api_key {
+            let provider = crate::shipping::carriers::UspsProvider::new(
+                api_key.clone(),
+            ).with_test_mode(usps.sandbox || test_mode);
+            providers.push(Box::new(provider));
+        }
+    }
+    
+    providers
+        .into_iter()
+        .map(|provider| (provider.id().to_string(), provider))
+        .collect()
 }
 
 impl Default for ShippingProviderFactory {
@@ -518,11 +694,23 @@ impl Default for ShippingProviderFactory {
 /// Multi-provider rate aggregator
 pub struct ShippingRateAggregator {
     factory: ShippingProviderFactory,
+    pickup: Vec<PickupLocation>,
 }
 
 impl ShippingRateAggregator {
     pub fn new(factory: ShippingProviderFactory) -> Self {
-        Self { factory }
+        Self { factory, pickup: Vec::new() }
+    }
+    
+    /// Also offer free pickup at these locations
+    pub fn with_pickup_locations(mut self, locations: Vec<PickupLocation>) -> Self {
+        self.pickup = locations;
+        self
+    }
+    
+    fn pickup_rates(&self, options: &RateOptions) -> Vec<ShippingRate> {
+        let currency = options.currency.as_deref().unwrap_or("USD");
+        self.pickup.iter().map(|location| pickup_rate(location, currency)).collect()
     }
     
     /// Get rates from all available providers
@@ -543,6 +731,7 @@ impl ShippingRateAggregator {
                 }
             }
         }
+        all_rates.extend(self.pickup_rates(options));
         
         // Sort by total cost
         all_rates.sort_by(|a, b| a.total_cost.cmp(&b.total_cost));
@@ -562,6 +751,10 @@ impl ShippingRateAggregator {
         let mut all_rates = Vec::new();
         
         for id in provider_ids {
+            if id == PICKUP_PROVIDER {
+                all_rates.extend(self.pickup_rates(options));
+                continue;
+            }
             let provider = self.factory.get(id)?;
             match provider.get_rates(from_address, to_address, package, options).await {
                 Ok(mut rates) => all_rates.append(&mut rates),
@@ -605,4 +798,57 @@ mod tests {
         assert!(ShipmentStatus::Cancelled.is_terminal());
         assert!(!ShipmentStatus::InTransit.is_terminal());
     }
+
+    #[test]
+    fn test_location_carrier_accounts() {
+        let config: crate::config::ShippingConfig = toml::from_str(
+            r#"
+            [origin]
+            name = "Main Warehouse"
+            address1 = "1 Main St"
+            city = "Austin"
+            state = "TX"
+            country = "US"
+            zip = "78701"
+
+            [ups]
+            enabled = true
+            api_key = "key"
+            username = "user"
+            password = "secret"
+            account_number = "US123"
+
+            [usps]
+            enabled = true
+            api_key = "usps-key"
+
+            [[locations]]
+            location = "EU-WH"
+
+            [locations.origin]
+            name = "EU Warehouse"
+            address1 = "Hafenstrasse 1"
+            city = "Hamburg"
+            state = "HH"
+            country = "DE"
+            zip = "20457"
+
+            [locations.usps]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        let factory = ShippingProviderFactory::from_config(&config);
+
+        // The location keeps the store's UPS account but not USPS
+        assert!(factory.get_for_location(Some("eu-wh"), "ups").is_ok());
+        assert!(factory.get_for_location(Some("EU-WH"), "usps").is_err());
+        assert!(factory.get_for_location(None, "usps").is_ok());
+        // Locations without their own accounts use the store's
+        assert!(factory.get_for_location(Some("US-WH"), "usps").is_ok());
+
+        assert_eq!(factory.origin(Some("EU-WH")).unwrap().country, "DE");
+        assert_eq!(factory.origin(Some("US-WH")).unwrap().country, "US");
+        assert_eq!(factory.origin(None).unwrap().country, "US");
+    }
 }
@@ -0,0 +1,304 @@
+//! In-store and local pickup
+//!
+//! Inventory locations with `pickup_enabled` are pickup locations. A
+//! location is offered at checkout as a free `pickup` shipping rate (its
+//! code is the service code) when it has every item of the cart in stock.
+//! A pickup order is packed from its location; staff mark its shipment
+//! ready for pickup, then picked up, and the customer is emailed at each
+//! step (`fulfillment.notify_customers`).
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ShippingRate;
+use crate::models::FulfillmentStatus;
+use crate::notification::service::NotificationFactory;
+use crate::notification::Recipient;
+use crate::order::LocationStock;
+use crate::repository::{NotificationRepository, PickupRepository};
+use crate::{Error, Result};
+
+/// Provider id of pickup shipping rates
+pub const PICKUP_PROVIDER: &str = "pickup";
+
+/// An inventory location customers can collect orders from
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PickupLocation {
+    pub id: Uuid,
+    pub name: String,
+    pub code: String,
+    pub address: Option<serde_json::Value>,
+    /// Where and how to collect, e.g. "Ask at the service desk"
+    pub pickup_instructions: Option<String>,
+    /// Opening hours for pickup, e.g. "Mon-Sat 9:00-18:00"
+    pub pickup_hours: Option<String>,
+}
+
+/// Units of a product in stock at a pickup location
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PickupAvailability {
+    pub location_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub available: i32,
+}
+
+/// Units of a product wanted for pickup
+#[derive(Debug, Clone, Copy)]
+pub struct PickupLine {
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub quantity: i32,
+}
+
+/// Pickup settings of an inventory location; unset fields are left as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdatePickupLocation {
+    pub pickup_enabled: Option<bool>,
+    pub pickup_instructions: Option<String>,
+    pub pickup_hours: Option<String>,
+}
+
+/// A shipment of a pickup order
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PickupShipment {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub email: String,
+    /// Customer's email opt-in (guests always get order emails)
+    pub email_notifications: bool,
+    pub status: FulfillmentStatus,
+    /// The order's pickup location; None if the order ships
+    pub location_id: Option<Uuid>,
+    pub location_name: Option<String>,
+    pub pickup_instructions: Option<String>,
+    pub pickup_hours: Option<String>,
+    pub ready_for_pickup_at: Option<DateTime<Utc>>,
+    pub picked_up_at: Option<DateTime<Utc>>,
+}
+
+/// The free shipping rate of collecting at `location`
+pub fn pickup_rate(location: &PickupLocation, currency: &str) -> ShippingRate {
+    let mut rate = ShippingRate::new(
+        PICKUP_PROVIDER,
+        location.name.clone(),
+        location.code.clone(),
+        format!("Pickup at {}", location.name),
+        Decimal::ZERO,
+        currency,
+    );
+    rate.estimated = false;
+    rate
+}
+
+/// Whether a shipping rate is collecting at a pickup location
+pub fn is_pickup_rate(rate: &ShippingRate) -> bool {
+    rate.provider_id == PICKUP_PROVIDER
+}
+
+/// Locations with every line in stock
+fn locations_stocking(lines: &[PickupLine], stock: &[LocationStock]) -> Vec<Uuid> {
+    let mut locations: Vec<Uuid> = stock.iter().map(|stock| stock.location_id).collect();
+    locations.sort();
+    locations.dedup();
+    locations.retain(|location_id| {
+        lines.iter().all(|line| {
+            let wanted: i32 = lines
+                .iter()
+                .filter(|other| other.product_id == line.product_id && other.variant_id == line.variant_id)
+                .map(|other| other.quantity)
+                .sum();
+            stock.iter().any(|stock| {
+                stock.location_id == *location_id
+                    && stock.product_id == line.product_id
+                    && stock.variant_id == line.variant_id
+                    && stock.available >= wanted
+            })
+        })
+    });
+    locations
+}
+
+/// Pickup locations with every line in stock
+pub async fn pickup_locations_for(repository: &dyn PickupRepository, lines: &[PickupLine]) -> Result<Vec<PickupLocation>> {
+    if lines.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut products: Vec<Uuid> = lines.iter().map(|line| line.product_id).collect();
+    products.sort();
+    products.dedup();
+    let stocking = locations_stocking(lines, &repository.stock(&products).await?);
+    let mut locations = repository.locations().await?;
+    locations.retain(|location| stocking.contains(&location.id));
+    Ok(locations)
+}
+
+/// The pickup location of a pickup rate, checked to have every line in stock
+pub async fn pickup_location_for_rate(
+    repository: &dyn PickupRepository,
+    rate: &ShippingRate,
+    lines: &[PickupLine],
+) -> Result<PickupLocation> {
+    pickup_locations_for(repository, lines)
+        .await?
+        .into_iter()
+        .find(|location| location.code == rate.service_code)
+        .ok_or_else(|| {
+            Error::validation(format!(
+                "Pickup at {} is not available for everything in the cart",
+                rate.service_code
+            ))
+        })
+}
+
+/// Pickup locations, availability and the pickup lifecycle of orders
+pub struct PickupService<R: PickupRepository> {
+    repository: R,
+    notifications: Option<Arc<dyn NotificationRepository>>,
+}
+
+impl<R: PickupRepository> PickupService<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            notifications: None,
+        }
+    }
+
+    /// Email customers when their order is ready for pickup and when it is collected
+    pub fn with_notifications(mut self, notifications: Arc<dyn NotificationRepository>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Active pickup locations
+    pub async fn locations(&self) -> Result<Vec<PickupLocation>> {
+        self.repository.locations().await
+    }
+
+    /// Stock of a product (or one of its variants) at each pickup location
+    pub async fn availability(&self, product_id: Uuid, variant_id: Option<Uuid>) -> Result<Vec<PickupAvailability>> {
+        self.repository.availability(product_id, variant_id).await
+    }
+
+    /// Offer or stop offering pickup at an inventory location
+    pub async fn update_location(&self, location_id: Uuid, request: UpdatePickupLocation) -> Result<PickupLocation> {
+        let trimmed = |value: Option<String>| value.map(|value| value.trim().to_string());
+        let request = UpdatePickupLocation {
+            pickup_enabled: request.pickup_enabled,
+            pickup_instructions: trimmed(request.pickup_instructions),
+            pickup_hours: trimmed(request.pickup_hours),
+        };
+        if request.pickup_hours.as_ref().is_some_and(|hours| hours.chars().count() > 255) {
+            return Err(Error::validation("pickup_hours is longer than 255 characters"));
+        }
+        self.repository
+            .update_location(location_id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Inventory location not found"))
+    }
+
+    async fn get_shipment(&self, shipment_id: Uuid) -> Result<PickupShipment> {
+        let shipment = self
+            .repository
+            .find_shipment(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))?;
+        if shipment.location_id.is_none() {
+            return Err(Error::validation(format!("Order {} is not a pickup order", shipment.order_number)));
+        }
+        Ok(shipment)
+    }
+
+    /// The shipment is packed and waiting at its pickup location
+    pub async fn mark_ready(&self, shipment_id: Uuid) -> Result<PickupShipment> {
+        let shipment = self.get_shipment(shipment_id).await?;
+        if !matches!(shipment.status, FulfillmentStatus::Pending | FulfillmentStatus::Processing) {
+            return Err(Error::validation("Only shipments not yet ready can be marked ready for pickup"));
+        }
+        let shipment = self.repository.set_status(shipment_id, FulfillmentStatus::ReadyForPickup).await?;
+        self.notify(&shipment).await;
+        Ok(shipment)
+    }
+
+    /// The customer collected the shipment
+    pub async fn mark_picked_up(&self, shipment_id: Uuid) -> Result<PickupShipment> {
+        let shipment = self.get_shipment(shipment_id).await?;
+        if !matches!(shipment.status, FulfillmentStatus::ReadyForPickup) {
+            return Err(Error::validation("Only shipments ready for pickup can be picked up"));
+        }
+        let shipment = self.repository.set_status(shipment_id, FulfillmentStatus::PickedUp).await?;
+        self.notify(&shipment).await;
+        Ok(shipment)
+    }
+
+    async fn notify(&self, shipment: &PickupShipment) {
+        let Some(notifications) = &self.notifications else {
+            return;
+        };
+        if !shipment.email_notifications {
+            return;
+        }
+        let recipient = Recipient::email(shipment.email.clone(), None);
+        let notification = match shipment.status {
+            FulfillmentStatus::ReadyForPickup => NotificationFactory::ready_for_pickup(shipment, recipient),
+            FulfillmentStatus::PickedUp => NotificationFactory::picked_up(shipment, recipient),
+            _ => return,
+        };
+        if let Err(e) = notifications.create(&notification).await {
+            tracing::warn!("Failed to queue pickup notice for order {}: {}", shipment.order_number, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stock(location: u128, product: u128, available: i32) -> LocationStock {
+        LocationStock {
+            location_id: Uuid::from_u128(location),
+            product_id: Uuid::from_u128(product),
+            variant_id: None,
+            available,
+        }
+    }
+
+    fn line(product: u128, quantity: i32) -> PickupLine {
+        PickupLine {
+            product_id: Uuid::from_u128(product),
+            variant_id: None,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_locations_stocking_every_line() {
+        let stock = vec![stock(1, 10, 5), stock(1, 11, 1), stock(2, 10, 5), stock(3, 10, 1), stock(3, 11, 4)];
+        assert_eq!(locations_stocking(&[line(10, 2), line(11, 1)], &stock), vec![Uuid::from_u128(1)]);
+        // The same product on two lines needs both quantities
+        assert_eq!(locations_stocking(&[line(10, 3), line(10, 3)], &stock), Vec::<Uuid>::new());
+        assert_eq!(locations_stocking(&[line(10, 1)], &stock).len(), 3);
+    }
+
+    #[test]
+    fn test_pickup_rate_is_free() {
+        let location = PickupLocation {
+            id: Uuid::nil(),
+            name: "Downtown Store".to_string(),
+            code: "DT".to_string(),
+            address: None,
+            pickup_instructions: None,
+            pickup_hours: None,
+        };
+        let rate = pickup_rate(&location, "USD");
+        assert!(is_pickup_rate(&rate));
+        assert_eq!(rate.total_cost, Decimal::ZERO);
+        assert_eq!(rate.service_code, "DT");
+    }
+}
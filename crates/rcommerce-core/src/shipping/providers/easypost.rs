@@ -4,7 +4,9 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
-use crate::Result;
+use serde::Deserialize;
+
+use crate::{Error, Result};
 use crate::common::Address;
 use crate::shipping::{
     ShippingProvider, ShippingRate, Shipment, TrackingInfo, TrackingStatus, TrackingEvent,
@@ -130,6 +132,29 @@ impl ShippingProvider for EasyPostProvider {
         })
     }
     
+    fn has_tracking_webhooks(&self) -> bool { true }
+    
+    async fn register_tracking_webhook(&self, url: &str) -> Result<()> {
+        self.client
+            .post(format!("{}/v2/webhooks", self.base_url))
+            .basic_auth(&self.api_key, Some(""))
+            .json(&serde_json::json!({ "webhook": { "url": url } }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+    
+    async fn parse_tracking_webhook(&self, body: &[u8]) -> Result<Vec<TrackingInfo>> {
+        let event: EasyPostEvent = serde_json::from_slice(body)
+            .map_err(|e| Error::validation(format!("Invalid EasyPost event: {}", e)))?;
+        // Other events (batches, refunds...) carry no tracking
+        if !event.description.starts_with("tracker.") {
+            return Ok(Vec::new());
+        }
+        Ok(event.result.into_iter().map(EasyPostTracker::into_tracking).collect())
+    }
+    
     async fn cancel_shipment(&self, _shipment_id: &str) -> Result<bool> { Ok(true) }
     
     async fn validate_address(&self, _address: &Address) -> Result<AddressValidation> {
@@ -158,3 +183,112 @@ impl ShippingProvider for EasyPostProvider {
         Ok(Some(Utc::now() + chrono::Duration::days(days)))
     }
 }
+
+/// Webhook event; trackers come as `tracker.created` and `tracker.updated`
+#[derive(Debug, Deserialize)]
+struct EasyPostEvent {
+    description: String,
+    result: Option<EasyPostTracker>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EasyPostTracker {
+    tracking_code: String,
+    status: String,
+    #[serde(default)]
+    carrier: Option<String>,
+    #[serde(default)]
+    est_delivery_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tracking_details: Vec<EasyPostTrackingDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EasyPostTrackingDetail {
+    #[serde(default)]
+    message: String,
+    status: String,
+    datetime: DateTime<Utc>,
+    #[serde(default)]
+    tracking_location: Option<EasyPostLocation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EasyPostLocation {
+    city: Option<String>,
+    state: Option<String>,
+    country: Option<String>,
+}
+
+impl EasyPostTracker {
+    fn into_tracking(self) -> TrackingInfo {
+        TrackingInfo {
+            tracking_number: self.tracking_code,
+            carrier: self.carrier.unwrap_or_else(|| "EasyPost".to_string()),
+            status: easypost_status(&self.status),
+            events: self
+                .tracking_details
+                .into_iter()
+                .map(|detail| {
+                    let location = detail.tracking_location.unwrap_or_default();
+                    TrackingEvent {
+                        timestamp: detail.datetime,
+                        status: easypost_status(&detail.status),
+                        description: detail.message,
+                        location: location.city.clone(),
+                        city: location.city,
+                        state: location.state,
+                        country: location.country,
+                    }
+                })
+                .collect(),
+            estimated_delivery: self.est_delivery_date,
+        }
+    }
+}
+
+fn easypost_status(status: &str) -> TrackingStatus {
+    match status {
+        "pre_transit" | "unknown" => TrackingStatus::PreTransit,
+        "in_transit" => TrackingStatus::InTransit,
+        "out_for_delivery" => TrackingStatus::OutForDelivery,
+        "delivered" => TrackingStatus::Delivered,
+        "available_for_pickup" => TrackingStatus::AvailableForPickup,
+        "return_to_sender" => TrackingStatus::ReturnToSender,
+        "failure" => TrackingStatus::Failure,
+        "cancelled" => TrackingStatus::Cancelled,
+        _ => TrackingStatus::Exception,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_tracker_updated() {
+        let body = br#"{
+            "object": "Event",
+            "description": "tracker.updated",
+            "result": {
+                "object": "Tracker",
+                "tracking_code": "9400110898825022579493",
+                "status": "delivered",
+                "carrier": "USPS",
+                "est_delivery_date": null,
+                "tracking_details": [
+                    {"message": "Delivered", "status": "delivered", "datetime": "2024-05-02T14:03:00Z",
+                     "tracking_location": {"city": "Denver", "state": "CO", "country": "US"}}
+                ]
+            }
+        }"#;
+        let updates = EasyPostProvider::new("key").parse_tracking_webhook(body).await.unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].tracking_number, "9400110898825022579493");
+        assert_eq!(updates[0].status, TrackingStatus::Delivered);
+        assert_eq!(updates[0].events[0].city.as_deref(), Some("Denver"));
+
+        let other = br#"{"description": "batch.updated", "result": null}"#;
+        assert!(EasyPostProvider::new("key").parse_tracking_webhook(other).await.unwrap().is_empty());
+    }
+}
@@ -7,7 +7,7 @@ use crate::order::Order;
 use crate::shipping::ShippingRate;
 
 /// Shipping rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShippingRule {
     pub name: String,
     pub condition: RuleCondition,
@@ -42,7 +42,7 @@ impl ShippingRule {
 }
 
 /// Rule condition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RuleCondition {
     /// Order total range
     OrderTotal { min: Option<Decimal>, max: Option<Decimal> },
@@ -125,7 +125,7 @@ impl RuleCondition {
 }
 
 /// Rule action
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RuleAction {
     /// Free shipping
     FreeShipping,
@@ -0,0 +1,363 @@
+//! Carrier tracking of shipments with a bought label
+//!
+//! Aggregators with webhooks (EasyPost, ShipStation) push tracking updates
+//! to [`TrackingService::handle_webhook`]; shipments of the other carriers
+//! are polled by the `tracking_poll` job. Either way the carrier's status
+//! is saved with the shipment, which moves forward to shipped, delivered or
+//! returned (never back). Each move is published as a
+//! `shipment.status_changed` event, whose notification handler tells the
+//! customer when a parcel ships and when it is delivered.
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{ShippingProviderFactory, TrackingInfo, TrackingStatus};
+use crate::cache::auth_session::constant_time_eq;
+use crate::config::TrackingConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
+use crate::events::{status_label, DomainEvent, EventBus, ShipmentStatusChanged};
+use crate::models::FulfillmentStatus;
+use crate::repository::TrackingRepository;
+use crate::{Error, Result};
+
+/// A shipment being tracked, with what notices need
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TrackedShipment {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub email: String,
+    pub email_notifications: bool,
+    /// Code of the location it ships from, for its carrier account
+    pub location_code: Option<String>,
+    pub tracking_company: Option<String>,
+    pub tracking_number: Option<String>,
+    pub tracking_url: Option<String>,
+    pub status: FulfillmentStatus,
+}
+
+/// A carrier status to save with a shipment
+#[derive(Debug, Clone)]
+pub struct TrackingRecord {
+    pub tracking_status: TrackingStatus,
+    /// Latest tracking event, e.g. "Arrived at facility, Memphis"
+    pub detail: Option<String>,
+    pub estimated_delivery: Option<DateTime<Utc>>,
+    /// The shipment's new status, if it moves
+    pub status: Option<FulfillmentStatus>,
+}
+
+/// What a tracking webhook changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrackingWebhookResult {
+    /// Tracking updates in the payload
+    pub updates: usize,
+    pub shipments_updated: usize,
+    /// Updates of tracking numbers no shipment has
+    pub unknown: usize,
+}
+
+/// What a `tracking_poll` run did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrackingPollReport {
+    pub checked: usize,
+    pub shipped: usize,
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+impl fmt::Display for TrackingPollReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked {} shipments: {} shipped, {} delivered, {} failed",
+            self.checked, self.shipped, self.delivered, self.failed
+        )
+    }
+}
+
+/// Registering the webhook with one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookRegistration {
+    pub provider: String,
+    pub url: String,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// The status a shipment moves to on a carrier status, if it moves;
+/// shipments only move forward
+pub fn next_status(current: FulfillmentStatus, tracking: TrackingStatus) -> Option<FulfillmentStatus> {
+    let next = match tracking {
+        TrackingStatus::InTransit | TrackingStatus::OutForDelivery | TrackingStatus::AvailableForPickup => {
+            FulfillmentStatus::Shipped
+        }
+        TrackingStatus::Delivered => FulfillmentStatus::Delivered,
+        TrackingStatus::ReturnToSender => FulfillmentStatus::Returned,
+        _ => return None,
+    };
+    let moves = match current {
+        FulfillmentStatus::Pending | FulfillmentStatus::Processing | FulfillmentStatus::Partial => {
+            matches!(next, FulfillmentStatus::Shipped | FulfillmentStatus::Delivered)
+        }
+        FulfillmentStatus::Shipped => matches!(next, FulfillmentStatus::Delivered | FulfillmentStatus::Returned),
+        _ => false,
+    };
+    moves.then_some(next)
+}
+
+/// Shipment tracking service
+pub struct TrackingService<R: TrackingRepository> {
+    repository: R,
+    shipping: Arc<ShippingProviderFactory>,
+    config: TrackingConfig,
+    events: Option<EventBus>,
+}
+
+impl<R: TrackingRepository> TrackingService<R> {
+    pub fn new(repository: R, shipping: Arc<ShippingProviderFactory>, config: TrackingConfig) -> Self {
+        Self {
+            repository,
+            shipping,
+            config,
+            events: None,
+        }
+    }
+
+    /// Publish the shipments' status changes
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn config(&self) -> &TrackingConfig {
+        &self.config
+    }
+
+    /// The URL a provider posts its tracking updates to
+    pub fn webhook_url(&self, provider: &str) -> String {
+        format!(
+            "{}/api/v1/shipping/tracking/{}?token={}",
+            self.config.public_url.trim_end_matches('/'),
+            provider,
+            self.config.webhook_token
+        )
+    }
+
+    /// Register the webhook URL with every provider that has webhooks
+    pub async fn register_webhooks(&self) -> Result<Vec<WebhookRegistration>> {
+        if !self.config.enabled {
+            return Err(Error::config("Shipment tracking is disabled (shipping.tracking.enabled)"));
+        }
+        let mut registrations = Vec::new();
+        for provider in self.shipping.get_all() {
+            if !provider.has_tracking_webhooks() {
+                continue;
+            }
+            let url = self.webhook_url(provider.id());
+            let result = provider.register_tracking_webhook(&url).await;
+            registrations.push(WebhookRegistration {
+                provider: provider.id().to_string(),
+                // The token stays out of responses and logs
+                url: url.split('?').next().unwrap_or_default().to_string(),
+                registered: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        Ok(registrations)
+    }
+
+    /// Apply a provider's tracking webhook
+    pub async fn handle_webhook(&self, provider: &str, token: &str, body: &[u8]) -> Result<TrackingWebhookResult> {
+        if !self.config.enabled {
+            return Err(Error::not_found("Shipment tracking is disabled"));
+        }
+        if self.config.webhook_token.is_empty()
+            || !constant_time_eq(self.config.webhook_token.as_bytes(), token.as_bytes())
+        {
+            return Err(Error::unauthorized("Invalid tracking webhook token"));
+        }
+        let updates = self.shipping.get(provider)?.parse_tracking_webhook(body).await?;
+
+        let mut result = TrackingWebhookResult {
+            updates: updates.len(),
+            ..Default::default()
+        };
+        for info in &updates {
+            let shipments = self
+                .repository
+                .find_by_tracking_number(provider, &info.tracking_number)
+                .await?;
+            if shipments.is_empty() {
+                result.unknown += 1;
+            }
+            for shipment in &shipments {
+                self.apply(shipment, info).await?;
+                result.shipments_updated += 1;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Ask the carrier for a shipment's tracking now
+    pub async fn refresh(&self, shipment_id: Uuid) -> Result<TrackingInfo> {
+        let shipment = self
+            .repository
+            .find(shipment_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Shipment not found"))?;
+        let info = self.track(&shipment).await?;
+        self.apply(&shipment, &info).await?;
+        Ok(info)
+    }
+
+    /// Poll the carriers without webhooks for shipments not yet delivered
+    pub async fn poll(&self) -> Result<TrackingPollReport> {
+        let providers: Vec<String> = self
+            .shipping
+            .get_all()
+            .into_iter()
+            .filter(|provider| !provider.has_tracking_webhooks())
+            .map(|provider| provider.id().to_string())
+            .collect();
+        let mut report = TrackingPollReport::default();
+        if !self.config.enabled || providers.is_empty() {
+            return Ok(report);
+        }
+
+        let since = Utc::now() - Duration::days(self.config.stop_after_days);
+        let shipments = self
+            .repository
+            .shipments_to_poll(&providers, since, self.config.poll_batch_size)
+            .await?;
+        for shipment in &shipments {
+            report.checked += 1;
+            let info = match self.track(shipment).await {
+                Ok(info) => info,
+                Err(e) => {
+                    tracing::warn!("Failed to track shipment {}: {}", shipment.id, e);
+                    report.failed += 1;
+                    self.repository.mark_checked(shipment.id).await?;
+                    continue;
+                }
+            };
+            match self.apply(shipment, &info).await? {
+                Some(FulfillmentStatus::Shipped) => report.shipped += 1,
+                Some(FulfillmentStatus::Delivered) => report.delivered += 1,
+                _ => {}
+            }
+        }
+        Ok(report)
+    }
+
+    async fn track(&self, shipment: &TrackedShipment) -> Result<TrackingInfo> {
+        let (Some(company), Some(number)) = (&shipment.tracking_company, &shipment.tracking_number) else {
+            return Err(Error::validation("Shipment has no tracking number"));
+        };
+        self.shipping
+            .get_for_location(shipment.location_code.as_deref(), company)?
+            .track_shipment(number)
+            .await
+    }
+
+    /// Save a carrier status with a shipment; returns the status it moved to
+    async fn apply(&self, shipment: &TrackedShipment, info: &TrackingInfo) -> Result<Option<FulfillmentStatus>> {
+        let status = next_status(shipment.status, info.status);
+        let detail = info.events.last().map(|event| match &event.location {
+            Some(location) => format!("{}, {}", event.description, location),
+            None => event.description.clone(),
+        });
+        let record = TrackingRecord {
+            tracking_status: info.status,
+            detail,
+            estimated_delivery: info.estimated_delivery,
+            status,
+        };
+        self.repository.record(shipment.id, &record).await?;
+
+        self.publish(shipment, status, info);
+        Ok(status)
+    }
+
+    /// Publish a move; carrier updates that don't move the shipment are only saved
+    fn publish(&self, shipment: &TrackedShipment, status: Option<FulfillmentStatus>, info: &TrackingInfo) {
+        let (Some(events), Some(status)) = (&self.events, status) else {
+            return;
+        };
+        events.publish(DomainEvent::ShipmentStatusChanged(ShipmentStatusChanged {
+            shipment_id: shipment.id,
+            order_id: shipment.order_id,
+            order_number: shipment.order_number.clone(),
+            email: shipment.email.clone(),
+            email_notifications: shipment.email_notifications,
+            status: status_label(&status),
+            previous_status: status_label(&shipment.status),
+            tracking_status: Some(info.status.as_str().to_string()),
+            tracking_company: shipment.tracking_company.clone(),
+            tracking_number: shipment.tracking_number.clone(),
+            tracking_url: shipment.tracking_url.clone(),
+            estimated_delivery: info.estimated_delivery,
+        }));
+    }
+}
+
+/// The `tracking_poll` recurring job
+pub struct TrackingPollJob<R: TrackingRepository> {
+    tracking: Arc<TrackingService<R>>,
+}
+
+impl<R: TrackingRepository> TrackingPollJob<R> {
+    pub fn new(tracking: Arc<TrackingService<R>>) -> Self {
+        Self { tracking }
+    }
+}
+
+#[async_trait]
+impl<R: TrackingRepository + 'static> RecurringJob for TrackingPollJob<R> {
+    fn name(&self) -> &str {
+        "tracking_poll"
+    }
+
+    fn description(&self) -> &str {
+        "Poll carriers without tracking webhooks for shipment updates"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.tracking.config().poll_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.tracking.poll().await?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_status_only_moves_forward() {
+        assert!(matches!(
+            next_status(FulfillmentStatus::Pending, TrackingStatus::InTransit),
+            Some(FulfillmentStatus::Shipped)
+        ));
+        assert!(matches!(
+            next_status(FulfillmentStatus::Shipped, TrackingStatus::Delivered),
+            Some(FulfillmentStatus::Delivered)
+        ));
+        assert!(matches!(
+            next_status(FulfillmentStatus::Shipped, TrackingStatus::ReturnToSender),
+            Some(FulfillmentStatus::Returned)
+        ));
+        // Already shipped, or an update arriving after delivery
+        assert!(next_status(FulfillmentStatus::Shipped, TrackingStatus::OutForDelivery).is_none());
+        assert!(next_status(FulfillmentStatus::Delivered, TrackingStatus::InTransit).is_none());
+        assert!(next_status(FulfillmentStatus::Processing, TrackingStatus::PreTransit).is_none());
+        assert!(next_status(FulfillmentStatus::Cancelled, TrackingStatus::Delivered).is_none());
+    }
+}
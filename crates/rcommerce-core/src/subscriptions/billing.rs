@@ -0,0 +1,281 @@
+//! Subscription renewal billing
+//!
+//! [`BillingEngine`] renews subscriptions whose `next_billing_at` has passed
+//! (including trials that have ended): it invoices the next cycle through
+//! [`SubscriptionService`], charges the saved payment method through a
+//! [`SubscriptionCharger`] and hands failed charges to [`DunningService`],
+//! which schedules retries and eventually cancels. A run also charges the
+//! dunning retries that have come due.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::config::SubscriptionBillingConfig;
+use crate::models::{
+    DunningConfig, PaymentRecoveryResult, Subscription, SubscriptionInvoice, SubscriptionStatus,
+    UpdateSubscriptionRequest,
+};
+use crate::payment::agnostic::{
+    CaptureMethod, InitiatePaymentRequest, InitiatePaymentResponse, PaymentMethodData, PaymentMethodType, PaymentService,
+};
+use crate::repository::SubscriptionRepository;
+use crate::services::{DunningService, RetryProcessingResult, SubscriptionService};
+use crate::{Error, Result};
+
+/// Outcome of charging an invoice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ChargeResult {
+    Succeeded { payment_id: String },
+    Failed { error_code: Option<String>, message: String },
+}
+
+impl From<InitiatePaymentResponse> for ChargeResult {
+    fn from(response: InitiatePaymentResponse) -> Self {
+        match response {
+            InitiatePaymentResponse::Success { payment_id, .. } => ChargeResult::Succeeded { payment_id },
+            // Nobody is present to complete 3DS or a redirect on a renewal
+            InitiatePaymentResponse::RequiresAction { .. } => ChargeResult::Failed {
+                error_code: Some("requires_action".to_string()),
+                message: "Payment requires customer action".to_string(),
+            },
+            InitiatePaymentResponse::Failed { error_code, error_message, .. } => ChargeResult::Failed {
+                error_code: Some(error_code),
+                message: error_message,
+            },
+        }
+    }
+}
+
+/// Charges a subscription invoice to the subscription's saved payment method
+#[async_trait]
+pub trait SubscriptionCharger: Send + Sync {
+    /// Charge an invoice. Declines are `Ok(ChargeResult::Failed)`; `Err` is
+    /// reserved for errors that are not the payment method's fault.
+    async fn charge(&self, subscription: &Subscription, invoice: &SubscriptionInvoice) -> Result<ChargeResult>;
+}
+
+/// Charges invoices through the subscription's payment gateway
+pub struct GatewayCharger {
+    payments: Arc<PaymentService>,
+    db: sqlx::PgPool,
+}
+
+impl GatewayCharger {
+    pub fn new(payments: Arc<PaymentService>, db: sqlx::PgPool) -> Self {
+        Self { payments, db }
+    }
+}
+
+#[async_trait]
+impl SubscriptionCharger for GatewayCharger {
+    async fn charge(&self, subscription: &Subscription, invoice: &SubscriptionInvoice) -> Result<ChargeResult> {
+        let Some(ref token) = subscription.payment_method_id else {
+            return Ok(ChargeResult::Failed {
+                error_code: Some("no_payment_method".to_string()),
+                message: "Subscription has no saved payment method".to_string(),
+            });
+        };
+
+        let gateway = self
+            .payments
+            .get_gateway(Some(&subscription.gateway))
+            .ok_or_else(|| Error::payment_error(format!("Payment gateway '{}' is not configured", subscription.gateway)))?;
+
+        let customer_email = sqlx::query_scalar::<_, String>("SELECT email FROM customers WHERE id = $1")
+            .bind(subscription.customer_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to get customer: {}", e)))?
+            .ok_or_else(|| Error::not_found("Customer not found"))?;
+
+        let response = gateway
+            .initiate_payment(InitiatePaymentRequest {
+                amount: invoice.total,
+                currency: subscription.currency.to_string(),
+                payment_method_type: PaymentMethodType::Card,
+                order_id: invoice.order_id.unwrap_or(subscription.order_id),
+                customer_id: Some(subscription.customer_id),
+                customer_email,
+                customer_ip: None,
+                billing_address: None,
+                shipping_address: None,
+                payment_method_data: PaymentMethodData::CardToken { token: token.clone() },
+                save_payment_method: false,
+                description: format!("Subscription {} renewal (cycle {})", subscription.id, invoice.cycle_number),
+                metadata: serde_json::json!({
+                    "subscription_id": subscription.id,
+                    "invoice_id": invoice.id,
+                    "cycle_number": invoice.cycle_number,
+                }),
+                // Off-session renewals have no customer to send through a challenge
+                return_url: None,
+                capture_method: CaptureMethod::Automatic,
+            })
+            .await?;
+
+        Ok(response.into())
+    }
+}
+
+/// Outcome of renewing one subscription
+#[derive(Debug, Clone)]
+pub enum RenewalOutcome {
+    /// The new cycle was invoiced and paid
+    Paid,
+    /// The charge failed and was handed to dunning
+    Failed(PaymentRecoveryResult),
+    /// The subscription had reached `max_cycles` and was expired instead
+    Expired,
+}
+
+/// Summary of a billing run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BillingReport {
+    pub renewed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    /// Subscriptions that could not be invoiced
+    pub errors: usize,
+    /// Dunning retries charged in this run
+    pub retries: Option<RetryProcessingResult>,
+}
+
+/// Subscription renewal engine
+pub struct BillingEngine<R: SubscriptionRepository + Clone> {
+    subscriptions: SubscriptionService<R>,
+    repository: R,
+    dunning: DunningService<R>,
+    charger: Arc<dyn SubscriptionCharger>,
+    config: SubscriptionBillingConfig,
+}
+
+impl<R: SubscriptionRepository + Clone> BillingEngine<R> {
+    pub fn new(
+        repository: R,
+        charger: Arc<dyn SubscriptionCharger>,
+        config: SubscriptionBillingConfig,
+        dunning: DunningConfig,
+    ) -> Self {
+        Self {
+            subscriptions: SubscriptionService::with_dunning_config(repository.clone(), dunning.clone()),
+            dunning: DunningService::with_config(repository.clone(), dunning).with_charger(charger.clone()),
+            repository,
+            charger,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &SubscriptionBillingConfig {
+        &self.config
+    }
+
+    pub fn dunning(&self) -> &DunningService<R> {
+        &self.dunning
+    }
+
+    /// Renew a subscription: invoice its next cycle and charge it
+    pub async fn renew(&self, subscription: &Subscription) -> Result<RenewalOutcome> {
+        if !subscription.status.is_billable() {
+            return Err(Error::validation(format!(
+                "Subscription {} cannot be billed while {:?}",
+                subscription.id, subscription.status
+            )));
+        }
+
+        if cycles_exhausted(subscription) {
+            self.repository
+                .update(subscription.id, UpdateSubscriptionRequest {
+                    status: Some(SubscriptionStatus::Expired),
+                    ..Default::default()
+                })
+                .await?;
+            return Ok(RenewalOutcome::Expired);
+        }
+
+        let invoice = self.subscriptions.process_billing_cycle(subscription).await?;
+
+        let charge = self.charger.charge(subscription, &invoice).await.unwrap_or_else(|e| {
+            tracing::error!("Failed to charge subscription {} invoice {}: {}", subscription.id, invoice.id, e);
+            ChargeResult::Failed { error_code: None, message: e.to_string() }
+        });
+
+        match charge {
+            ChargeResult::Succeeded { payment_id } => {
+                self.subscriptions.record_payment(subscription.id, invoice.id, payment_id).await?;
+                Ok(RenewalOutcome::Paid)
+            }
+            ChargeResult::Failed { message, .. } => {
+                let result = self.dunning.process_failed_payment(subscription.id, invoice.id, &message).await?;
+                Ok(RenewalOutcome::Failed(result))
+            }
+        }
+    }
+
+    /// Renew every subscription due for billing
+    pub async fn renew_due(&self) -> Result<BillingReport> {
+        let due = self.repository.get_due_for_billing(chrono::Utc::now()).await?;
+        let mut report = BillingReport::default();
+
+        for subscription in due {
+            match self.renew(&subscription).await {
+                Ok(RenewalOutcome::Paid) => report.renewed += 1,
+                Ok(RenewalOutcome::Failed(_)) => report.failed += 1,
+                Ok(RenewalOutcome::Expired) => report.expired += 1,
+                Err(e) => {
+                    tracing::error!("Failed to renew subscription {}: {}", subscription.id, e);
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Renew due subscriptions, then charge due dunning retries
+    pub async fn run(&self) -> Result<BillingReport> {
+        let mut report = self.renew_due().await?;
+        report.retries = Some(self.dunning.process_all_due_retries().await?);
+        Ok(report)
+    }
+}
+
+/// Whether a subscription has been billed `max_cycles` times
+fn cycles_exhausted(subscription: &Subscription) -> bool {
+    subscription.max_cycles.is_some_and(|max| subscription.current_cycle >= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_result_from_gateway_response() {
+        let declined = InitiatePaymentResponse::Failed {
+            payment_id: "pay_1".to_string(),
+            error_code: "card_declined".to_string(),
+            error_message: "Your card was declined".to_string(),
+            retry_allowed: true,
+        };
+        assert_eq!(
+            ChargeResult::from(declined),
+            ChargeResult::Failed {
+                error_code: Some("card_declined".to_string()),
+                message: "Your card was declined".to_string(),
+            }
+        );
+
+        let three_ds = InitiatePaymentResponse::RequiresAction {
+            payment_id: "pay_2".to_string(),
+            action_type: crate::payment::agnostic::PaymentActionType::ThreeDSecure,
+            action_data: serde_json::json!({}),
+            expires_at: chrono::Utc::now(),
+        };
+        assert!(matches!(
+            ChargeResult::from(three_ds),
+            ChargeResult::Failed { error_code: Some(code), .. } if code == "requires_action"
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+//! Subscription status transitions
+//!
+//! ```text
+//! pending -> trialing -> active <-> paused
+//!                |          |
+//!                +--> past_due --> active (payment recovered)
+//!
+//! any non-terminal status -> cancelled; active/past_due -> expired
+//! ```
+
+use crate::models::SubscriptionStatus;
+
+impl SubscriptionStatus {
+    /// Cancelled and expired subscriptions never change again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, SubscriptionStatus::Cancelled | SubscriptionStatus::Expired)
+    }
+
+    /// Whether renewals are invoiced and charged in this status
+    pub fn is_billable(&self) -> bool {
+        matches!(self, SubscriptionStatus::Active | SubscriptionStatus::Trialing)
+    }
+
+    pub fn can_transition_to(&self, new_status: SubscriptionStatus) -> bool {
+        use SubscriptionStatus::*;
+
+        match (self, new_status) {
+            // Terminal states are final
+            (Cancelled | Expired, _) => false,
+
+            // Unchanged status is always allowed
+            (from, to) if *from == to => true,
+
+            // Can cancel from any non-terminal state
+            (_, Cancelled) => true,
+
+            // Pending transitions
+            (Pending, Trialing | Active) => true,
+
+            // Trial ends with the first renewal
+            (Trialing, Active | PastDue | Paused) => true,
+
+            // Active transitions
+            (Active, Paused | PastDue | Expired) => true,
+
+            // Paused transitions
+            (Paused, Active) => true,
+
+            // Past due recovers or runs out of cycles
+            (PastDue, Active | Expired) => true,
+
+            // No other transitions allowed
+            _ => false,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::Pending => "Awaiting activation",
+            SubscriptionStatus::Trialing => "In trial, first renewal charged when it ends",
+            SubscriptionStatus::Active => "Active and renewing",
+            SubscriptionStatus::Paused => "Paused, no renewals are charged",
+            SubscriptionStatus::PastDue => "Renewal payment failed, retrying",
+            SubscriptionStatus::Cancelled => "Cancelled",
+            SubscriptionStatus::Expired => "Ended after its last billing cycle",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_transitions() {
+        use SubscriptionStatus::*;
+
+        assert!(Trialing.can_transition_to(Active));
+        assert!(Active.can_transition_to(Paused));
+        assert!(Paused.can_transition_to(Active));
+        assert!(PastDue.can_transition_to(Active));
+        assert!(Paused.can_transition_to(Cancelled));
+
+        assert!(!Paused.can_transition_to(PastDue));
+        assert!(!Active.can_transition_to(Trialing));
+        assert!(!Cancelled.can_transition_to(Active));
+        assert!(!Expired.can_transition_to(Expired));
+    }
+
+    #[test]
+    fn test_billable_statuses() {
+        assert!(SubscriptionStatus::Trialing.is_billable());
+        assert!(SubscriptionStatus::Active.is_billable());
+        assert!(!SubscriptionStatus::Paused.is_billable());
+        assert!(!SubscriptionStatus::PastDue.is_billable());
+    }
+}
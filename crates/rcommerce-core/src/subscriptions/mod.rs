@@ -0,0 +1,21 @@
+//! Subscription billing
+//!
+//! Subscription records live in `models::subscription` and are managed by
+//! `SubscriptionService`. This module adds what turns them into recurring
+//! billing:
+//!
+//! - `plan` - reusable plans (product, interval, price, trial) customers subscribe to
+//! - `lifecycle` - allowed status changes (trialing, active, paused, past due, cancelled, expired)
+//! - `billing` - renewal runs that invoice due subscriptions, charge the saved
+//!   payment method through the payment gateway and hand failed renewals to
+//!   the `DunningService`
+
+pub mod billing;
+pub mod lifecycle;
+pub mod plan;
+
+pub use billing::{BillingEngine, BillingReport, ChargeResult, GatewayCharger, RenewalOutcome, SubscriptionCharger};
+pub use plan::{
+    SubscriptionPlan, SubscriptionPlanService, CreateSubscriptionPlanRequest,
+    UpdateSubscriptionPlanRequest, SubscribeToPlanRequest,
+};
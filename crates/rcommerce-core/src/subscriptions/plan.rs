@@ -0,0 +1,224 @@
+//! Subscription plans
+//!
+//! A plan is the catalog entry customers subscribe to: a product billed
+//! every `interval_count` intervals at a fixed price, optionally after a
+//! trial. Subscribing copies the plan's terms onto the subscription, so
+//! editing a plan's price or trial only affects new subscribers, and
+//! deactivating a plan stops new sign-ups without touching existing ones.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{CreateSubscriptionRequest, Currency, Subscription, SubscriptionInterval};
+use crate::repository::{SubscriptionPlanRepository, SubscriptionRepository};
+use crate::services::SubscriptionService;
+use crate::{Error, Result};
+
+/// Subscription plan
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SubscriptionPlan {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub interval: SubscriptionInterval,
+    pub interval_count: i32,
+    pub currency: Currency,
+    pub amount: Decimal,
+    pub setup_fee: Option<Decimal>,
+    pub trial_days: i32,
+    pub min_cycles: Option<i32>,
+    pub max_cycles: Option<i32>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create plan request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateSubscriptionPlanRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub description: Option<String>,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    pub interval: SubscriptionInterval,
+    #[validate(range(min = 1, max = 12))]
+    pub interval_count: i32,
+    pub currency: Currency,
+    pub amount: Decimal,
+    pub setup_fee: Option<Decimal>,
+    #[validate(range(min = 0, max = 365))]
+    pub trial_days: Option<i32>,
+    #[validate(range(min = 1))]
+    pub min_cycles: Option<i32>,
+    #[validate(range(min = 1))]
+    pub max_cycles: Option<i32>,
+}
+
+/// Update plan request; changes apply to new subscribers only
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateSubscriptionPlanRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub amount: Option<Decimal>,
+    pub setup_fee: Option<Decimal>,
+    #[validate(range(min = 0, max = 365))]
+    pub trial_days: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// Customer request to subscribe to a plan
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SubscribeToPlanRequest {
+    /// Order that paid for the first cycle (or setup fee)
+    pub order_id: Uuid,
+    /// Saved payment method (gateway token) renewals are charged to
+    #[validate(length(min = 1))]
+    pub payment_method_id: String,
+    pub gateway: String,
+    pub notes: Option<String>,
+}
+
+impl SubscriptionPlan {
+    /// Subscription request with this plan's terms
+    pub fn subscription_request(&self, customer_id: Uuid, request: SubscribeToPlanRequest) -> CreateSubscriptionRequest {
+        CreateSubscriptionRequest {
+            customer_id,
+            order_id: request.order_id,
+            product_id: self.product_id,
+            variant_id: self.variant_id,
+            plan_id: Some(self.id),
+            interval: self.interval,
+            interval_count: self.interval_count,
+            currency: self.currency,
+            amount: self.amount,
+            setup_fee: self.setup_fee,
+            trial_days: Some(self.trial_days),
+            min_cycles: self.min_cycles,
+            max_cycles: self.max_cycles,
+            payment_method_id: request.payment_method_id,
+            gateway: request.gateway,
+            notes: request.notes,
+        }
+    }
+}
+
+/// Subscription plan service
+pub struct SubscriptionPlanService<P: SubscriptionPlanRepository> {
+    repository: P,
+}
+
+impl<P: SubscriptionPlanRepository> SubscriptionPlanService<P> {
+    pub fn new(repository: P) -> Self {
+        Self { repository }
+    }
+
+    /// Create a plan
+    pub async fn create_plan(&self, request: CreateSubscriptionPlanRequest) -> Result<SubscriptionPlan> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.amount < Decimal::ZERO || request.setup_fee.is_some_and(|f| f < Decimal::ZERO) {
+            return Err(Error::validation("Plan prices must not be negative"));
+        }
+        if let (Some(min), Some(max)) = (request.min_cycles, request.max_cycles) {
+            if min > max {
+                return Err(Error::validation("min_cycles cannot be greater than max_cycles"));
+            }
+        }
+
+        self.repository.create(&request).await
+    }
+
+    /// Get a plan
+    pub async fn get_plan(&self, id: Uuid) -> Result<SubscriptionPlan> {
+        self.repository
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Subscription plan not found"))
+    }
+
+    /// List plans, optionally only those open for sign-up
+    pub async fn list_plans(&self, active_only: bool) -> Result<Vec<SubscriptionPlan>> {
+        self.repository.list(active_only).await
+    }
+
+    /// Update a plan
+    pub async fn update_plan(&self, id: Uuid, request: UpdateSubscriptionPlanRequest) -> Result<SubscriptionPlan> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+        if request.amount.is_some_and(|a| a < Decimal::ZERO) || request.setup_fee.is_some_and(|f| f < Decimal::ZERO) {
+            return Err(Error::validation("Plan prices must not be negative"));
+        }
+
+        self.repository
+            .update(id, &request)
+            .await?
+            .ok_or_else(|| Error::not_found("Subscription plan not found"))
+    }
+
+    /// Subscribe a customer to an active plan
+    pub async fn subscribe<R: SubscriptionRepository>(
+        &self,
+        subscriptions: &SubscriptionService<R>,
+        plan_id: Uuid,
+        customer_id: Uuid,
+        request: SubscribeToPlanRequest,
+    ) -> Result<Subscription> {
+        request.validate().map_err(|e| Error::validation(e.to_string()))?;
+
+        let plan = self.get_plan(plan_id).await?;
+        if !plan.is_active {
+            return Err(Error::validation("Subscription plan is no longer available"));
+        }
+
+        subscriptions
+            .create_subscription(plan.subscription_request(customer_id, request))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_request_copies_plan_terms() {
+        let plan = SubscriptionPlan {
+            id: Uuid::new_v4(),
+            name: "Coffee club".to_string(),
+            description: None,
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            interval: SubscriptionInterval::Monthly,
+            interval_count: 1,
+            currency: Currency::EUR,
+            amount: Decimal::new(1990, 2),
+            setup_fee: None,
+            trial_days: 14,
+            min_cycles: Some(3),
+            max_cycles: None,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let customer_id = Uuid::new_v4();
+
+        let request = plan.subscription_request(customer_id, SubscribeToPlanRequest {
+            order_id: Uuid::new_v4(),
+            payment_method_id: "pm_123".to_string(),
+            gateway: "stripe".to_string(),
+            notes: None,
+        });
+
+        assert_eq!(request.customer_id, customer_id);
+        assert_eq!(request.plan_id, Some(plan.id));
+        assert_eq!(request.amount, plan.amount);
+        assert_eq!(request.trial_days, Some(14));
+        assert_eq!(request.min_cycles, Some(3));
+    }
+}
@@ -0,0 +1,317 @@
+//! Landed Cost
+//!
+//! Duties and import taxes of cross-border orders, quoted at checkout so
+//! customers can prepay them (DDP) or pay the carrier on delivery (DAP).
+
+use async_trait::async_trait;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::LandedCostConfig;
+use crate::Result;
+
+/// Who pays duties and import taxes of an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "incoterm", rename_all = "lowercase")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Incoterm {
+    /// Delivered duty paid: charged at checkout, the store settles with customs
+    Ddp,
+    /// Delivered at place: the customer pays the carrier on delivery
+    #[default]
+    Dap,
+}
+
+impl std::fmt::Display for Incoterm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Incoterm::Ddp => write!(f, "DDP"),
+            Incoterm::Dap => write!(f, "DAP"),
+        }
+    }
+}
+
+/// A line of goods crossing the border
+#[derive(Debug, Clone)]
+pub struct LandedCostLine {
+    pub item_id: Uuid,
+    /// Harmonized System code of the product, if classified
+    pub hs_code: Option<String>,
+    /// ISO country the goods were made in (default the ship-from country)
+    pub origin_country: Option<String>,
+    /// Customs value of the line (price times quantity, after discounts)
+    pub value: Decimal,
+}
+
+/// Goods to quote landed cost for
+#[derive(Debug, Clone)]
+pub struct LandedCostRequest {
+    /// ISO country the order ships to
+    pub destination_country: String,
+    pub currency: String,
+    pub lines: Vec<LandedCostLine>,
+    /// Shipping charged, part of the value import taxes apply to
+    pub shipping: Decimal,
+    /// Checkout already charged the destination's VAT/GST (e.g. under IOSS)
+    pub tax_collected: bool,
+}
+
+/// Duty on one line
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DutyLine {
+    pub item_id: Uuid,
+    pub hs_code: Option<String>,
+    pub rate: Decimal,
+    pub duty: Decimal,
+}
+
+/// Duties and import taxes due on a cross-border order
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LandedCostQuote {
+    pub destination_country: String,
+    pub currency: String,
+    pub duties: Decimal,
+    pub import_taxes: Decimal,
+    /// Handling fee charged with DDP
+    pub ddp_fee: Decimal,
+    pub lines: Vec<DutyLine>,
+    /// Incoterms the customer can choose from
+    pub incoterms: Vec<Incoterm>,
+    pub default_incoterm: Incoterm,
+}
+
+impl LandedCostQuote {
+    /// What checkout charges for duties, import taxes and fees
+    pub fn charge(&self, incoterm: Incoterm) -> Decimal {
+        match incoterm {
+            Incoterm::Ddp => self.duties + self.import_taxes + self.ddp_fee,
+            Incoterm::Dap => Decimal::ZERO,
+        }
+    }
+
+    /// Whether the customer can choose `incoterm`
+    pub fn offers(&self, incoterm: Incoterm) -> bool {
+        self.incoterms.contains(&incoterm)
+    }
+}
+
+/// Source of landed cost quotes (the built-in estimator, or a
+/// cross-border service such as Zonos or Avalara)
+#[async_trait]
+pub trait LandedCostProvider: Send + Sync {
+    /// Provider name
+    fn name(&self) -> &str;
+
+    /// Quote duties and import taxes, or `None` for domestic orders
+    async fn quote(&self, request: &LandedCostRequest) -> Result<Option<LandedCostQuote>>;
+}
+
+/// Estimates landed cost from configured duty rates by HS code
+pub struct HsCodeEstimator {
+    config: LandedCostConfig,
+    origin_country: String,
+}
+
+impl HsCodeEstimator {
+    /// Estimator for orders shipping from `origin_country`
+    pub fn new(config: LandedCostConfig, origin_country: impl Into<String>) -> Self {
+        Self {
+            config,
+            origin_country: origin_country.into(),
+        }
+    }
+
+    /// Duty rate of goods under `hs_code` shipped to `country`
+    fn duty_rate(&self, country: &str, hs_code: Option<&str>) -> Decimal {
+        let hs_code = hs_code.unwrap_or_default();
+        self.config
+            .duty_rates
+            .iter()
+            .filter(|duty| duty.country.eq_ignore_ascii_case(country) && hs_code.starts_with(duty.hs_code.as_str()))
+            .max_by_key(|duty| duty.hs_code.len())
+            .map(|duty| duty.rate)
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// VAT/GST rate on imports into `country`
+    fn import_tax_rate(&self, country: &str) -> Decimal {
+        self.config
+            .import_tax_rates
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(country))
+            .map(|(_, rate)| *rate)
+            .or_else(|| super::get_eu_vat_rate(country, "standard"))
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    fn de_minimis(&self, country: &str) -> Decimal {
+        self.config
+            .de_minimis
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(country))
+            .map(|(_, value)| *value)
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Shipments inside the EU customs union are not imports
+    fn is_domestic(&self, destination: &str) -> bool {
+        destination.eq_ignore_ascii_case(&self.origin_country)
+            || (super::is_eu_country(&destination.to_uppercase())
+                && super::is_eu_country(&self.origin_country.to_uppercase()))
+    }
+}
+
+#[async_trait]
+impl LandedCostProvider for HsCodeEstimator {
+    fn name(&self) -> &str {
+        "hs_code_estimator"
+    }
+
+    async fn quote(&self, request: &LandedCostRequest) -> Result<Option<LandedCostQuote>> {
+        let destination = request.destination_country.as_str();
+        if !self.config.enabled || self.is_domestic(destination) {
+            return Ok(None);
+        }
+
+        let goods_value: Decimal = request.lines.iter().map(|line| line.value).sum();
+        let dutiable = goods_value > self.de_minimis(destination);
+        let lines: Vec<DutyLine> = request
+            .lines
+            .iter()
+            .map(|line| {
+                // Goods made in the destination country return duty free
+                let returning = line
+                    .origin_country
+                    .as_deref()
+                    .is_some_and(|origin| origin.eq_ignore_ascii_case(destination));
+                let rate = if dutiable && !returning {
+                    self.duty_rate(destination, line.hs_code.as_deref())
+                } else {
+                    Decimal::ZERO
+                };
+                DutyLine {
+                    item_id: line.item_id,
+                    hs_code: line.hs_code.clone(),
+                    rate,
+                    duty: round(line.value * rate),
+                }
+            })
+            .collect();
+
+        let duties: Decimal = lines.iter().map(|line| line.duty).sum();
+        let import_taxes = if request.tax_collected {
+            Decimal::ZERO
+        } else {
+            round((goods_value + request.shipping + duties) * self.import_tax_rate(destination))
+        };
+        let incoterms = if self.config.offer_ddp {
+            vec![Incoterm::Ddp, Incoterm::Dap]
+        } else {
+            vec![Incoterm::Dap]
+        };
+
+        Ok(Some(LandedCostQuote {
+            destination_country: destination.to_uppercase(),
+            currency: request.currency.clone(),
+            duties,
+            import_taxes,
+            ddp_fee: self.config.ddp_fee,
+            lines,
+            incoterms,
+            default_incoterm: self.config.default_incoterm,
+        }))
+    }
+}
+
+fn round(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DutyRateConfig;
+    use rust_decimal_macros::dec;
+
+    fn estimator() -> HsCodeEstimator {
+        let config = LandedCostConfig {
+            enabled: true,
+            ddp_fee: dec!(5),
+            duty_rates: vec![
+                DutyRateConfig { country: "US".into(), hs_code: String::new(), rate: dec!(0.05) },
+                DutyRateConfig { country: "US".into(), hs_code: "6109".into(), rate: dec!(0.165) },
+            ],
+            de_minimis: [("US".to_string(), dec!(0))].into(),
+            import_tax_rates: [("GB".to_string(), dec!(0.20))].into(),
+            ..LandedCostConfig::default()
+        };
+        HsCodeEstimator::new(config, "DE")
+    }
+
+    fn line(hs_code: Option<&str>, value: Decimal) -> LandedCostLine {
+        LandedCostLine {
+            item_id: Uuid::new_v4(),
+            hs_code: hs_code.map(str::to_string),
+            origin_country: None,
+            value,
+        }
+    }
+
+    fn request(country: &str, lines: Vec<LandedCostLine>) -> LandedCostRequest {
+        LandedCostRequest {
+            destination_country: country.to_string(),
+            currency: "EUR".to_string(),
+            lines,
+            shipping: dec!(10),
+            tax_collected: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duty_by_longest_hs_prefix() {
+        let quote = estimator()
+            .quote(&request("us", vec![line(Some("610910"), dec!(100)), line(None, dec!(40))]))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(quote.lines[0].rate, dec!(0.165));
+        assert_eq!(quote.lines[1].rate, dec!(0.05));
+        assert_eq!(quote.duties, dec!(18.50));
+        assert_eq!(quote.import_taxes, dec!(0));
+        assert_eq!(quote.charge(Incoterm::Ddp), dec!(23.50));
+        assert_eq!(quote.charge(Incoterm::Dap), dec!(0));
+        assert!(quote.offers(Incoterm::Ddp));
+    }
+
+    #[tokio::test]
+    async fn test_de_minimis_and_import_tax() {
+        let mut estimator = estimator();
+        estimator.config.de_minimis.insert("GB".to_string(), dec!(150));
+        estimator.config.duty_rates.push(DutyRateConfig { country: "GB".into(), hs_code: String::new(), rate: dec!(0.12) });
+
+        let quote = estimator.quote(&request("GB", vec![line(None, dec!(100))])).await.unwrap().unwrap();
+        assert_eq!(quote.duties, dec!(0));
+        // VAT on goods and shipping
+        assert_eq!(quote.import_taxes, dec!(22.00));
+
+        let mut collected = request("GB", vec![line(None, dec!(200))]);
+        collected.tax_collected = true;
+        let quote = estimator.quote(&collected).await.unwrap().unwrap();
+        assert_eq!(quote.duties, dec!(24.00));
+        assert_eq!(quote.import_taxes, dec!(0));
+    }
+
+    #[tokio::test]
+    async fn test_domestic_and_returning_goods() {
+        let estimator = estimator();
+        assert!(estimator.quote(&request("DE", vec![line(None, dec!(100))])).await.unwrap().is_none());
+        assert!(estimator.quote(&request("FR", vec![line(None, dec!(100))])).await.unwrap().is_none());
+
+        let mut made_in_us = line(Some("6109"), dec!(100));
+        made_in_us.origin_country = Some("US".to_string());
+        let quote = estimator.quote(&request("US", vec![made_in_us])).await.unwrap().unwrap();
+        assert_eq!(quote.duties, dec!(0));
+    }
+}
@@ -10,14 +10,16 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 pub mod calculator;
+pub mod landed_cost;
 pub mod models;
 pub mod providers;
 pub mod service;
 pub mod vat_validation;
 
 pub use calculator::{TaxCalculator, TaxCalculation, LineItemTax, TaxBreakdown};
+pub use landed_cost::{HsCodeEstimator, Incoterm, LandedCostLine, LandedCostProvider, LandedCostQuote, LandedCostRequest};
 pub use models::*;
-pub use service::{TaxService, DefaultTaxService};
+pub use service::{TaxService, DefaultTaxService, VatReverificationJob, VatReverificationSummary};
 pub use vat_validation::{VatId, VatValidationResult, ViesValidator};
 
 use crate::Result;
@@ -2,8 +2,12 @@
 //!
 //! Main tax service implementation for calculating taxes and managing tax data.
 
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::Decimal;
 use sqlx::PgPool;
 use tracing::{debug, info, warn};
@@ -13,8 +17,14 @@ use crate::tax::{
     calculator::TaxCalculator, models::*, vat_validation::*, OssReport,
     OssScheme, OssTransaction, OssSummary, CountrySummary, TaxAddress, TaxCalculation, TaxCategory, TaxContext, TaxRate, TaxZone, TaxableItem, VatId,
 };
+use crate::cache::VatIdCache;
+use crate::config::TaxConfig;
+use crate::jobs::recurring::{interval_schedule, RecurringJob};
 use crate::{Error, Result};
 
+/// VAT IDs re-verified per job run
+const REVERIFY_BATCH_SIZE: i64 = 100;
+
 /// Tax service trait
 #[async_trait]
 pub trait TaxService: Send + Sync {
@@ -71,23 +81,231 @@ pub trait TaxService: Send + Sync {
 pub struct DefaultTaxService {
     db: PgPool,
     vat_validator: ViesValidator,
+    vat_config: TaxConfig,
+    vat_cache: VatIdCache,
 }
 
 impl DefaultTaxService {
     /// Create a new tax service
     pub fn new(db: PgPool) -> Self {
-        Self {
-            db,
-            vat_validator: ViesValidator::new(),
-        }
+        Self::with_validator(db, ViesValidator::new())
     }
 
     /// Create with custom VAT validator
     pub fn with_validator(db: PgPool, validator: ViesValidator) -> Self {
+        let vat_config = TaxConfig::default();
         Self {
             db,
             vat_validator: validator,
+            vat_cache: vat_cache(&vat_config),
+            vat_config,
+        }
+    }
+
+    /// Use the VAT validation settings of `[tax]`
+    pub fn with_vat_config(mut self, config: &TaxConfig) -> Self {
+        self.vat_cache = vat_cache(config);
+        self.vat_config = config.clone();
+        self
+    }
+
+    pub fn vat_config(&self) -> &TaxConfig {
+        &self.vat_config
+    }
+
+    /// Save a VIES answer: the validation cache, the history and any queued
+    /// re-verification
+    async fn record_vat_result(&self, vat_id: &VatId, result: &VatValidationResult, source: &str) -> Result<()> {
+        let full_id = vat_id.full_id();
+        sqlx::query(
+            r#"
+            INSERT INTO vat_id_validations
+            (vat_id, country_code, business_name, business_address, is_valid, validated_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (vat_id) DO UPDATE SET
+            business_name = EXCLUDED.business_name,
+            business_address = EXCLUDED.business_address,
+            is_valid = EXCLUDED.is_valid,
+            validated_at = EXCLUDED.validated_at,
+            expires_at = EXCLUDED.expires_at
+            "#
+        )
+        .bind(&full_id)
+        .bind(&result.country_code)
+        .bind(&result.business_name)
+        .bind(&result.business_address)
+        .bind(result.is_valid)
+        .bind(result.validated_at)
+        .bind(result.validated_at + Duration::days(self.vat_config.vat_cache_days))
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to cache VAT validation: {}", e)))?;
+
+        let status = if result.is_valid { "valid" } else { "invalid" };
+        self.record_vat_history(vat_id, status, result, source).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE vat_reverifications
+            SET resolved_at = NOW(), is_valid = $2
+            WHERE vat_id = $1 AND resolved_at IS NULL
+            "#
+        )
+        .bind(&full_id)
+        .bind(result.is_valid)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve VAT re-verification: {}", e)))?;
+
+        self.vat_cache.insert(&full_id, result);
+        Ok(())
+    }
+
+    /// Add a check to the VAT validation history
+    async fn record_vat_history(
+        &self,
+        vat_id: &VatId,
+        status: &str,
+        result: &VatValidationResult,
+        source: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO vat_validations
+            (vat_id, country_code, status, business_name, business_address, error_message, source)
+            VALUES ($1, $2, $3::vat_validation_status, $4, $5, $6, $7)
+            "#
+        )
+        .bind(vat_id.full_id())
+        .bind(&vat_id.country_code)
+        .bind(status)
+        .bind(&result.business_name)
+        .bind(&result.business_address)
+        .bind(&result.error_message)
+        .bind(source)
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to record VAT validation: {}", e)))?;
+        Ok(())
+    }
+
+    /// VIES couldn't be reached at checkout: queue the VAT ID for
+    /// re-verification and accept it provisionally if configured to
+    async fn vat_unavailable(&self, vat_id: &VatId, message: String) -> Result<VatValidationResult> {
+        warn!("VIES unavailable for {}: {}", vat_id.full_id(), message);
+        let result = VatValidationResult {
+            is_valid: self.vat_config.accept_vat_ids_when_vies_unavailable,
+            country_code: vat_id.country_code.clone(),
+            vat_number: vat_id.number.clone(),
+            business_name: None,
+            business_address: None,
+            validated_at: Utc::now(),
+            error_message: Some(message.clone()),
+            provisional: true,
+        };
+        self.record_vat_history(vat_id, "unavailable", &result, "checkout").await?;
+
+        // Restart the queue entry of a VAT ID that was resolved before
+        sqlx::query(
+            r#"
+            INSERT INTO vat_reverifications (vat_id, country_code, last_error, next_attempt_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (vat_id) DO UPDATE SET
+            attempts = 0,
+            last_error = EXCLUDED.last_error,
+            next_attempt_at = EXCLUDED.next_attempt_at,
+            created_at = NOW(),
+            resolved_at = NULL,
+            is_valid = NULL
+            WHERE vat_reverifications.resolved_at IS NOT NULL
+            "#
+        )
+        .bind(vat_id.full_id())
+        .bind(&vat_id.country_code)
+        .bind(&message)
+        .bind(Utc::now() + reverify_delay(self.vat_config.vat_reverify_interval_secs, 0))
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to queue VAT re-verification: {}", e)))?;
+
+        if !self.vat_config.accept_vat_ids_when_vies_unavailable {
+            return Err(Error::Network(message));
         }
+        self.vat_cache.insert(&vat_id.full_id(), &result);
+        Ok(result)
+    }
+
+    /// Check the queued VAT IDs that are due against VIES again
+    pub async fn reverify_pending(&self) -> Result<VatReverificationSummary> {
+        let due: Vec<(String, i32)> = sqlx::query_as(
+            r#"
+            SELECT vat_id, attempts FROM vat_reverifications
+            WHERE resolved_at IS NULL AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT $1
+            "#
+        )
+        .bind(REVERIFY_BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::Other(format!("Failed to load VAT re-verifications: {}", e)))?;
+
+        let mut summary = VatReverificationSummary::default();
+        for (full_id, attempts) in due {
+            summary.checked += 1;
+            let vat_id = VatId::parse(&full_id)?;
+            let message = match self.vat_validator.validate(&vat_id).await {
+                Ok(result) => {
+                    self.record_vat_result(&vat_id, &result, "reverification").await?;
+                    if result.is_valid {
+                        summary.valid += 1;
+                    } else {
+                        warn!("VAT ID {} accepted while VIES was unavailable is invalid", full_id);
+                        summary.invalid += 1;
+                    }
+                    continue;
+                }
+                Err(e) => e.to_string(),
+            };
+
+            let failed = VatValidationResult {
+                is_valid: false,
+                country_code: vat_id.country_code.clone(),
+                vat_number: vat_id.number.clone(),
+                business_name: None,
+                business_address: None,
+                validated_at: Utc::now(),
+                error_message: Some(message.clone()),
+                provisional: true,
+            };
+            self.record_vat_history(&vat_id, "unavailable", &failed, "reverification").await?;
+
+            let attempts = attempts + 1;
+            let gave_up = attempts >= self.vat_config.vat_reverify_max_attempts;
+            if gave_up {
+                warn!("Giving up re-verifying VAT ID {} after {} attempts: {}", full_id, attempts, message);
+                summary.abandoned += 1;
+            } else {
+                summary.pending += 1;
+            }
+            sqlx::query(
+                r#"
+                UPDATE vat_reverifications
+                SET attempts = $2, last_error = $3, next_attempt_at = $4,
+                    resolved_at = CASE WHEN $5 THEN NOW() ELSE NULL END
+                WHERE vat_id = $1
+                "#
+            )
+            .bind(&full_id)
+            .bind(attempts)
+            .bind(&message)
+            .bind(Utc::now() + reverify_delay(self.vat_config.vat_reverify_interval_secs, attempts))
+            .bind(gave_up)
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to update VAT re-verification: {}", e)))?;
+        }
+        Ok(summary)
     }
 
     /// Get tax rates for a zone
@@ -239,24 +457,31 @@ impl TaxService for DefaultTaxService {
         // Parse VAT ID
         let vat_id = VatId::parse(vat_id_str)?;
 
+        let full_id = vat_id.full_id();
+        if let Some(result) = self.vat_cache.get(&full_id) {
+            debug!("Using in-memory VAT validation for {}", full_id);
+            return Ok(result);
+        }
+
         // Check cache first
         let cached: Option<VatValidationCache> = sqlx::query_as(
             r#"
             SELECT * FROM vat_id_validations
             WHERE vat_id = $1
-            AND validated_at > NOW() - INTERVAL '30 days'
+            AND validated_at > $2
             ORDER BY validated_at DESC
             LIMIT 1
             "#
         )
-        .bind(vat_id.full_id())
+        .bind(&full_id)
+        .bind(Utc::now() - Duration::days(self.vat_config.vat_cache_days))
         .fetch_optional(&self.db)
         .await
         .map_err(|e| Error::Other(format!("Failed to check VAT cache: {}", e)))?;
 
         if let Some(cache) = cached {
-            debug!("Using cached VAT validation for {}", vat_id.full_id());
-            return Ok(VatValidationResult {
+            debug!("Using cached VAT validation for {}", full_id);
+            let result = VatValidationResult {
                 is_valid: cache.is_valid,
                 country_code: cache.country_code,
                 vat_number: vat_id.number.clone(),
@@ -264,38 +489,21 @@ impl TaxService for DefaultTaxService {
                 business_address: cache.business_address,
                 validated_at: cache.validated_at,
                 error_message: None,
-            });
+                provisional: false,
+            };
+            self.vat_cache.insert(&full_id, &result);
+            return Ok(result);
         }
 
         // Validate via VIES
-        let result = self.vat_validator.validate(&vat_id).await?;
-
-        // Cache result
-        sqlx::query(
-            r#"
-            INSERT INTO vat_id_validations
-            (vat_id, country_code, business_name, business_address, is_valid, validated_at, expires_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            ON CONFLICT (vat_id) DO UPDATE SET
-            business_name = EXCLUDED.business_name,
-            business_address = EXCLUDED.business_address,
-            is_valid = EXCLUDED.is_valid,
-            validated_at = EXCLUDED.validated_at,
-            expires_at = EXCLUDED.expires_at
-            "#
-        )
-        .bind(vat_id.full_id())
-        .bind(&result.country_code)
-        .bind(&result.business_name)
-        .bind(&result.business_address)
-        .bind(result.is_valid)
-        .bind(result.validated_at)
-        .bind(result.validated_at + chrono::Duration::days(30))
-        .execute(&self.db)
-        .await
-        .map_err(|e| Error::Other(format!("Failed to cache VAT validation: {}", e)))?;
-
-        Ok(result)
+        match self.vat_validator.validate(&vat_id).await {
+            Ok(result) => {
+                self.record_vat_result(&vat_id, &result, "checkout").await?;
+                Ok(result)
+            }
+            Err(Error::Network(message)) => self.vat_unavailable(&vat_id, message).await,
+            Err(e) => Err(e),
+        }
     }
 
     async fn get_tax_rates(
@@ -368,8 +576,9 @@ impl TaxService for DefaultTaxService {
             scheme, period, member_state
         );
 
-        // Parse period (YYYY-MM) for validation
-        let (_year, _month): (i32, i32) = {
+        // Parse period (YYYY-MM), which also bounds the scan to the
+        // period's tax_transactions partition
+        let (year, month): (i32, u32) = {
             let parts: Vec<&str> = period.split('-').collect();
             if parts.len() != 2 {
                 return Err(Error::validation("Invalid period format, expected YYYY-MM"));
@@ -380,30 +589,40 @@ impl TaxService for DefaultTaxService {
             )
         };
 
+        let period_start = chrono::NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| Error::validation("Invalid month"))?;
+        let period_end = period_start + chrono::Months::new(1);
+
         let scheme_str = match scheme {
             OssScheme::Union => "union",
             OssScheme::NonUnion => "non_union",
             OssScheme::Import => "import",
         };
 
-        // Fetch transactions for period
+        // Fetch transactions for period, converted with the exchange rate
+        // snapshotted on each order rather than today's rate
         let transactions: Vec<OssTransactionRow> = sqlx::query_as(
             r#"
             SELECT 
-                country_code,
-                tax_rate,
-                SUM(taxable_amount) as taxable_amount,
-                SUM(tax_amount) as tax_amount,
+                t.country_code,
+                t.tax_rate,
+                ROUND(SUM(t.taxable_amount * COALESCE(o.exchange_rate, 1)), 4) as taxable_amount,
+                ROUND(SUM(t.tax_amount * COALESCE(o.exchange_rate, 1)), 4) as tax_amount,
                 COUNT(*) as transaction_count
-            FROM tax_transactions
-            WHERE oss_scheme = $1
-            AND oss_period = $2
-            GROUP BY country_code, tax_rate
-            ORDER BY country_code
+            FROM tax_transactions t
+            JOIN orders o ON o.id = t.order_id
+            WHERE t.oss_scheme = $1
+            AND t.oss_period = $2
+            AND t.created_at >= $3
+            AND t.created_at < $4
+            GROUP BY t.country_code, t.tax_rate
+            ORDER BY t.country_code
             "#
         )
         .bind(scheme_str)
         .bind(period)
+        .bind(period_start.and_time(chrono::NaiveTime::MIN).and_utc())
+        .bind(period_end.and_time(chrono::NaiveTime::MIN).and_utc())
         .fetch_all(&self.db)
         .await
         .map_err(|e| Error::Other(format!("Failed to fetch OSS transactions: {}", e)))?;
@@ -643,6 +862,74 @@ struct VatValidationCache {
     validated_at: DateTime<Utc>,
 }
 
+/// The in-memory VAT validation cache of a config; provisional results are
+/// kept until their re-verification is due
+fn vat_cache(config: &TaxConfig) -> VatIdCache {
+    VatIdCache::new(
+        StdDuration::from_secs(config.vat_cache_ttl_secs),
+        StdDuration::from_secs(config.vat_reverify_interval_secs),
+    )
+}
+
+/// Wait before the next re-verification after `attempts` failed ones:
+/// the interval, doubled after each failure
+fn reverify_delay(interval_secs: u64, attempts: i32) -> Duration {
+    let factor = 1i64 << attempts.clamp(0, 16);
+    Duration::seconds((interval_secs as i64).saturating_mul(factor))
+}
+
+/// Outcome of a re-verification run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VatReverificationSummary {
+    pub checked: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    /// VIES still unavailable; tried again later
+    pub pending: usize,
+    /// VIES still unavailable after the last attempt
+    pub abandoned: usize,
+}
+
+impl fmt::Display for VatReverificationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} VAT IDs checked: {} valid, {} invalid, {} still pending, {} given up",
+            self.checked, self.valid, self.invalid, self.pending, self.abandoned
+        )
+    }
+}
+
+/// Re-verifies VAT IDs accepted while VIES was unavailable
+pub struct VatReverificationJob {
+    tax: Arc<DefaultTaxService>,
+}
+
+impl VatReverificationJob {
+    pub fn new(tax: Arc<DefaultTaxService>) -> Self {
+        Self { tax }
+    }
+}
+
+#[async_trait]
+impl RecurringJob for VatReverificationJob {
+    fn name(&self) -> &str {
+        "vat_reverification"
+    }
+
+    fn description(&self) -> &str {
+        "Re-verify VAT IDs accepted while VIES was unavailable"
+    }
+
+    fn default_schedule(&self) -> String {
+        interval_schedule(self.tax.vat_config().vat_reverify_interval_secs)
+    }
+
+    async fn run(&self) -> Result<String> {
+        Ok(self.tax.reverify_pending().await?.to_string())
+    }
+}
+
 /// Get country name from code
 fn country_name(code: &str) -> String {
     let countries: std::collections::HashMap<&str, &str> = [
@@ -678,3 +965,16 @@ fn country_name(code: &str) -> String {
 
     countries.get(code).map(|&s| s.to_string()).unwrap_or_else(|| code.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverify_delay_doubles() {
+        assert_eq!(reverify_delay(900, 0), Duration::seconds(900));
+        assert_eq!(reverify_delay(900, 1), Duration::seconds(1800));
+        assert_eq!(reverify_delay(900, 3), Duration::seconds(7200));
+        assert_eq!(reverify_delay(900, 40), reverify_delay(900, 16));
+    }
+}
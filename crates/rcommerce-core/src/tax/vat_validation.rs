@@ -100,6 +100,10 @@ pub struct VatValidationResult {
     pub validated_at: DateTime<Utc>,
     /// Error message (if validation failed)
     pub error_message: Option<String>,
+    /// Accepted without a VIES answer (VIES was unavailable); the VAT ID is
+    /// re-verified in the background
+    #[serde(default)]
+    pub provisional: bool,
 }
 
 /// VIES VAT validation service
@@ -128,6 +132,16 @@ impl ViesValidator {
         }
     }
 
+    /// Create with a request timeout
+    pub fn with_timeout(timeout: std::time::Duration) -> Self {
+        Self::with_client(
+            reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("Failed to create HTTP client"),
+        )
+    }
+
     /// Validate a VAT ID using VIES
     pub async fn validate(&self, vat_id: &VatId) -> Result<VatValidationResult> {
         info!("Validating VAT ID: {}", vat_id.full_id());
@@ -244,6 +258,7 @@ impl ViesValidator {
             business_address,
             validated_at: Utc::now(),
             error_message: None,
+            provisional: false,
         })
     }
 
@@ -372,6 +387,7 @@ impl UkVatValidator {
             business_address: None,
             validated_at: Utc::now(),
             error_message: Some("UK VAT validation requires HMRC API credentials".to_string()),
+            provisional: false,
         })
     }
 }
@@ -0,0 +1,279 @@
+//! Live events for admin dashboards
+//!
+//! [`LiveEvents`] subscribes to the event bus and forwards order, payment,
+//! stock and shipment events - published here, relayed from other
+//! instances or sent by the stock triggers of migration 062 on the
+//! `live_events` Postgres channel - to a broadcast channel. Every admin
+//! WebSocket and storefront stock stream picks the topics it wants from there.
+
+use std::fmt;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::events::{BusEvent, EventHandler, HandlerScope};
+use crate::{Error, Result};
+
+/// Postgres channel the triggers notify
+pub const LIVE_EVENTS_CHANNEL: &str = "live_events";
+
+/// A topic admin dashboards can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LiveTopic {
+    #[serde(rename = "order.created")]
+    OrderCreated,
+    #[serde(rename = "payment.updated")]
+    PaymentUpdated,
+    #[serde(rename = "inventory.low_stock")]
+    InventoryLowStock,
+    #[serde(rename = "shipment.status_changed")]
+    ShipmentStatusChanged,
+    #[serde(rename = "stock.availability_changed")]
+    StockAvailabilityChanged,
+}
+
+impl LiveTopic {
+    pub const ALL: [LiveTopic; 5] = [
+        LiveTopic::OrderCreated,
+        LiveTopic::PaymentUpdated,
+        LiveTopic::InventoryLowStock,
+        LiveTopic::ShipmentStatusChanged,
+        LiveTopic::StockAvailabilityChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiveTopic::OrderCreated => "order.created",
+            LiveTopic::PaymentUpdated => "payment.updated",
+            LiveTopic::InventoryLowStock => "inventory.low_stock",
+            LiveTopic::ShipmentStatusChanged => "shipment.status_changed",
+            LiveTopic::StockAvailabilityChanged => "stock.availability_changed",
+        }
+    }
+
+    /// Admin route whose read permission grants the topic
+    pub fn admin_route(&self) -> &'static str {
+        match self {
+            LiveTopic::OrderCreated => "/admin/orders",
+            LiveTopic::PaymentUpdated => "/admin/payments",
+            LiveTopic::InventoryLowStock => "/admin/inventory",
+            LiveTopic::ShipmentStatusChanged => "/admin/shipments",
+            LiveTopic::StockAvailabilityChanged => "/admin/inventory",
+        }
+    }
+}
+
+impl fmt::Display for LiveTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LiveTopic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        LiveTopic::ALL
+            .into_iter()
+            .find(|topic| topic.as_str() == s.trim())
+            .ok_or_else(|| Error::validation(format!("Unknown topic: {}", s.trim())))
+    }
+}
+
+/// An event as sent to dashboards
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveEvent {
+    pub topic: LiveTopic,
+    pub data: serde_json::Value,
+    pub at: DateTime<Utc>,
+}
+
+impl LiveEvent {
+    pub fn from_bus(event: &BusEvent) -> Option<Self> {
+        Some(Self {
+            topic: event.event.name().parse().ok()?,
+            data: event.event.data(),
+            at: event.at,
+        })
+    }
+}
+
+/// Availability of a product or variant as shown on storefronts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StockStatus {
+    /// Current availability, sent when a stream starts
+    InStock,
+    OutOfStock,
+    /// Was out of stock and can be bought again
+    BackInStock,
+}
+
+impl StockStatus {
+    pub fn current(available: bool) -> Self {
+        if available {
+            StockStatus::InStock
+        } else {
+            StockStatus::OutOfStock
+        }
+    }
+}
+
+/// A `stock.availability_changed` event, or the availability a stream starts with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct StockUpdate {
+    pub product_id: Uuid,
+    /// Set when a variant's availability changed
+    pub variant_id: Option<Uuid>,
+    pub status: StockStatus,
+}
+
+impl StockUpdate {
+    pub fn from_event(event: &LiveEvent) -> Option<Self> {
+        if event.topic != LiveTopic::StockAvailabilityChanged {
+            return None;
+        }
+        serde_json::from_value(event.data.clone()).ok()
+    }
+}
+
+/// Messages from a dashboard
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveClientMessage {
+    Subscribe { topics: Vec<LiveTopic> },
+    Unsubscribe { topics: Vec<LiveTopic> },
+    Ping,
+}
+
+/// Messages to a dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveServerMessage {
+    /// Topics the connection is subscribed to after a (un)subscribe
+    Subscribed { topics: Vec<LiveTopic> },
+    Event(LiveEvent),
+    Error { message: String },
+    Pong,
+}
+
+/// Fan-out of live events to the connected dashboards
+#[derive(Clone)]
+pub struct LiveEvents {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl LiveEvents {
+    /// `buffer` is how many events a slow connection may fall behind by
+    pub fn new(buffer: usize) -> Self {
+        let (sender, _) = broadcast::channel(buffer.max(1));
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send an event to every connection; returns how many got it
+    pub fn publish(&self, event: LiveEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Open dashboard connections
+    pub fn connection_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[async_trait]
+impl EventHandler for LiveEvents {
+    fn name(&self) -> &'static str {
+        "live events"
+    }
+
+    /// Every instance pushes to its own dashboards
+    fn scope(&self) -> HandlerScope {
+        HandlerScope::Everywhere
+    }
+
+    async fn handle(&self, event: &BusEvent) -> Result<()> {
+        if let Some(event) = LiveEvent::from_bus(event) {
+            self.publish(event);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_and_messages() {
+        for topic in LiveTopic::ALL {
+            assert_eq!(topic.as_str().parse::<LiveTopic>().unwrap(), topic);
+            assert_eq!(serde_json::to_value(topic).unwrap(), topic.as_str());
+        }
+        assert!("order.deleted".parse::<LiveTopic>().is_err());
+
+        let message: LiveClientMessage =
+            serde_json::from_str(r#"{"type":"subscribe","topics":["order.created","inventory.low_stock"]}"#).unwrap();
+        assert_eq!(
+            message,
+            LiveClientMessage::Subscribe { topics: vec![LiveTopic::OrderCreated, LiveTopic::InventoryLowStock] }
+        );
+
+        let event: LiveEvent = serde_json::from_str(
+            r#"{"topic":"payment.updated","data":{"status":"completed"},"at":"2024-01-23T14:13:35.123456+00:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.topic, LiveTopic::PaymentUpdated);
+        let sent = serde_json::to_value(LiveServerMessage::Event(event)).unwrap();
+        assert_eq!(sent["type"], "event");
+        assert_eq!(sent["topic"], "payment.updated");
+    }
+
+    #[test]
+    fn test_stock_update_from_event() {
+        let product_id = Uuid::new_v4();
+        let event = LiveEvent {
+            topic: LiveTopic::StockAvailabilityChanged,
+            data: serde_json::json!({ "product_id": product_id, "variant_id": null, "status": "back_in_stock" }),
+            at: Utc::now(),
+        };
+        assert_eq!(
+            StockUpdate::from_event(&event),
+            Some(StockUpdate { product_id, variant_id: None, status: StockStatus::BackInStock })
+        );
+        assert_eq!(StockUpdate::from_event(&LiveEvent { topic: LiveTopic::OrderCreated, ..event }), None);
+        assert_eq!(StockStatus::current(false), StockStatus::OutOfStock);
+    }
+
+    #[test]
+    fn test_live_event_from_bus() {
+        let bus = crate::events::EventBus::new(&Default::default());
+        let update = StockUpdate { product_id: Uuid::new_v4(), variant_id: None, status: StockStatus::OutOfStock };
+        let published = bus.publish(crate::events::DomainEvent::StockAvailabilityChanged(update.clone()));
+
+        let event = LiveEvent::from_bus(&published).unwrap();
+        assert_eq!(event.topic, LiveTopic::StockAvailabilityChanged);
+        assert_eq!(event.at, published.at);
+        assert_eq!(StockUpdate::from_event(&event), Some(update));
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let live = LiveEvents::new(8);
+        let event = LiveEvent { topic: LiveTopic::OrderCreated, data: serde_json::json!({}), at: Utc::now() };
+        assert_eq!(live.publish(event.clone()), 0);
+
+        let mut receiver = live.subscribe();
+        assert_eq!(live.connection_count(), 1);
+        assert_eq!(live.publish(event.clone()), 1);
+        assert_eq!(receiver.recv().await.unwrap(), event);
+    }
+}
@@ -8,9 +8,11 @@ pub mod message;
 pub mod broadcast;
 pub mod pubsub;
 pub mod connection;
+pub mod live;
 
 pub use config::WebSocketConfig;
 pub use message::{WebSocketMessage, MessageType, MessagePayload, MessageCategory};
+pub use live::{LiveClientMessage, LiveEvent, LiveEvents, LiveServerMessage, LiveTopic, StockStatus, StockUpdate, LIVE_EVENTS_CHANNEL};
 
 use uuid::Uuid;
 
@@ -7,16 +7,42 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// How long a response with an ETag is kept after it goes stale, to be
+/// revalidated with `If-None-Match` instead of downloaded again
+pub const REVALIDATE_KEEP_SECS: u64 = 24 * 60 * 60;
+
 /// Cached response data
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CachedResponse {
     pub body: Vec<u8>,
     pub content_type: String,
     pub cached_at: DateTime<Utc>,
+    /// The API's ETag, sent back when revalidating
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// Seconds the response is served without asking the API
+    #[serde(default)]
+    pub fresh_secs: u64,
     #[serde(skip)]
     pub backend: String,
 }
 
+impl CachedResponse {
+    pub fn is_fresh(&self) -> bool {
+        let age = Utc::now().signed_duration_since(self.cached_at).num_seconds();
+        age >= 0 && (age as u64) < self.fresh_secs
+    }
+
+    /// How long to store the response: stale ones with an ETag stay for revalidation
+    pub fn keep_secs(&self) -> u64 {
+        if self.etag.is_some() {
+            self.fresh_secs + REVALIDATE_KEEP_SECS
+        } else {
+            self.fresh_secs
+        }
+    }
+}
+
 /// Cache backend trait
 #[async_trait]
 pub trait CacheBackend: Send + Sync {
@@ -53,9 +79,9 @@ impl Cache {
     }
 }
 
-/// In-memory LRU cache
+/// In-memory LRU cache; entries keep their own TTL (the default when 0)
 pub struct MemoryCache {
-    cache: RwLock<lru::LruCache<String, CachedResponse>>,
+    cache: RwLock<lru::LruCache<String, (CachedResponse, u64)>>,
     ttl_secs: u64,
 }
 
@@ -74,18 +100,19 @@ impl MemoryCache {
 impl CacheBackend for MemoryCache {
     async fn get(&self, key: &str) -> Result<Option<CachedResponse>> {
         let cache = self.cache.read().await;
-        if let Some(cached) = cache.peek(key) {
+        if let Some((cached, ttl_secs)) = cache.peek(key) {
             let age = Utc::now().signed_duration_since(cached.cached_at).num_seconds() as u64;
-            if age < self.ttl_secs {
+            if age < *ttl_secs {
                 return Ok(Some(cached.clone()));
             }
         }
         Ok(None)
     }
     
-    async fn set(&self, key: &str, value: &CachedResponse, _ttl_secs: u64) -> Result<()> {
+    async fn set(&self, key: &str, value: &CachedResponse, ttl_secs: u64) -> Result<()> {
+        let ttl = if ttl_secs > 0 { ttl_secs } else { self.ttl_secs };
         let mut cache = self.cache.write().await;
-        cache.put(key.to_string(), value.clone());
+        cache.put(key.to_string(), (value.clone(), ttl));
         Ok(())
     }
     
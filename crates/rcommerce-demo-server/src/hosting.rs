@@ -0,0 +1,621 @@
+//! Storefront hosting mode
+//!
+//! With `releases_dir` set, the server hosts a production storefront build
+//! instead of rendering the SSR templates. Each build is uploaded to its own
+//! directory, `releases_dir/<release>`, and the build pipeline then calls the
+//! deploy webhook. Deploying fingerprints the release's assets and renders
+//! its HTML before anything is switched, so a broken build never goes live;
+//! the switch itself is a pointer swap. Old releases stay on disk, and a
+//! rollback swaps back to the one before.
+//!
+//! HTML files are Tera templates, rendered once per deploy with:
+//! - `store`: the `[store]` settings, e.g. `{{ store.name }}`
+//! - `api_url`: the API endpoint browsers call (`public_api_url`)
+//! - `environment` and `release`
+//! - `runtime_config`: the values above as JSON, for
+//!   `<script>window.RCOMMERCE = {{ runtime_config | safe }}</script>`
+//! - `asset(path="css/app.css")`: the fingerprinted URL of an asset
+//!
+//! Files whose name starts with `_` are only templates (layouts, partials)
+//! and are not served. Fingerprinted asset URLs are cached forever; pages
+//! and unfingerprinted URLs are revalidated, so a deploy shows at once.
+
+use anyhow::{bail, Context as _, Result};
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use tera::{Context as TeraContext, Tera};
+use tracing::{info, warn};
+
+use crate::{AppState, Config, StoreSettings};
+
+/// Symlink in `releases_dir` naming the live release, so restarts keep it
+const CURRENT_LINK: &str = "current";
+
+/// Activated releases, oldest first; the last line is the live one
+const HISTORY_FILE: &str = ".history";
+
+/// Hex characters of the content hash put in asset names
+const FINGERPRINT_LENGTH: usize = 10;
+
+/// Values every page is rendered with
+#[derive(Debug, Clone)]
+pub struct TemplateSettings {
+    pub store: StoreSettings,
+    pub api_url: String,
+    pub environment: String,
+}
+
+impl TemplateSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            store: config.store.clone(),
+            api_url: config.public_api_url.clone(),
+            environment: config.environment.clone(),
+        }
+    }
+}
+
+/// A build ready to serve
+#[derive(Debug)]
+pub struct Release {
+    pub id: String,
+    root: PathBuf,
+    /// Asset path to its fingerprinted path, both relative
+    assets: HashMap<String, String>,
+    /// Fingerprinted path back to the asset path
+    fingerprinted: HashMap<String, String>,
+    /// Rendered pages by relative path
+    pages: HashMap<String, String>,
+    pub activated_at: DateTime<Utc>,
+}
+
+impl Release {
+    /// Fingerprint the assets under `root` and render its pages
+    pub fn load(id: &str, root: &Path, settings: &TemplateSettings) -> Result<Self> {
+        let mut files = Vec::new();
+        collect_files(root, root, &mut files)?;
+
+        let mut assets = HashMap::new();
+        let mut templates = Vec::new();
+        for (relative, path) in files {
+            if is_page(&relative) {
+                let source = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", relative))?;
+                templates.push((relative, source));
+            } else {
+                let content = std::fs::read(&path).with_context(|| format!("Failed to read {}", relative))?;
+                let fingerprinted = fingerprinted_path(&relative, &content);
+                assets.insert(relative, fingerprinted);
+            }
+        }
+        let fingerprinted = assets
+            .iter()
+            .map(|(asset, fingerprinted)| (fingerprinted.clone(), asset.clone()))
+            .collect();
+        let pages = render_pages(id, templates, &assets, settings)?;
+
+        Ok(Self {
+            id: id.to_string(),
+            root: root.to_path_buf(),
+            assets,
+            fingerprinted,
+            pages,
+            activated_at: Utc::now(),
+        })
+    }
+
+    /// What a request path resolves to in this release
+    fn resolve(&self, path: &str, spa_fallback: bool) -> Option<Resolved<'_>> {
+        if let Some(asset) = self.fingerprinted.get(path) {
+            return Some(Resolved::Asset { path: self.root.join(asset), immutable: true });
+        }
+        let candidates = if path.is_empty() {
+            vec!["index.html".to_string()]
+        } else {
+            let trimmed = path.trim_end_matches('/');
+            vec![path.to_string(), format!("{}.html", trimmed), format!("{}/index.html", trimmed)]
+        };
+        if let Some(page) = candidates.iter().find_map(|candidate| self.pages.get(candidate)) {
+            return Some(Resolved::Page(page));
+        }
+        if self.assets.contains_key(path) {
+            return Some(Resolved::Asset { path: self.root.join(path), immutable: false });
+        }
+        // Client-side routes of single-page apps; paths with an extension are missing files
+        let last_segment = path.rsplit('/').next().unwrap_or_default();
+        if spa_fallback && !last_segment.contains('.') {
+            return self.pages.get("index.html").map(|page| Resolved::Page(page));
+        }
+        None
+    }
+}
+
+enum Resolved<'a> {
+    Page(&'a str),
+    Asset { path: PathBuf, immutable: bool },
+}
+
+/// HTML files are Tera templates; the rest are assets
+fn is_page(relative: &str) -> bool {
+    relative.ends_with(".html") || relative.ends_with(".htm")
+}
+
+fn is_partial(relative: &str) -> bool {
+    relative.rsplit('/').next().is_some_and(|name| name.starts_with('_'))
+}
+
+/// Files under `dir` with their `/`-separated path relative to `root`;
+/// hidden files and symlinks are skipped
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            warn!("Skipping {}: not UTF-8", entry.path().display());
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// `css/app.css` becomes `css/app.<hash>.css`
+fn fingerprinted_path(relative: &str, content: &[u8]) -> String {
+    let hash = hex::encode(Sha256::digest(content));
+    let hash = &hash[..FINGERPRINT_LENGTH];
+    let (dir, name) = match relative.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), relative),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}{}.{}.{}", dir, stem, hash, extension),
+        _ => format!("{}{}.{}", dir, name, hash),
+    }
+}
+
+/// JSON that can't close a `<script>` element
+fn script_safe_json(value: &serde_json::Value) -> String {
+    value
+        .to_string()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+/// Render every page of a release; fails on any template error
+fn render_pages(
+    release: &str,
+    templates: Vec<(String, String)>,
+    assets: &HashMap<String, String>,
+    settings: &TemplateSettings,
+) -> Result<HashMap<String, String>> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(templates.iter().map(|(name, source)| (name.as_str(), source.as_str())))
+        .map_err(|e| anyhow::Error::new(e).context("Invalid template"))?;
+
+    let asset_urls = assets.clone();
+    tera.register_function("asset", move |args: &HashMap<String, tera::Value>| {
+        let path = args
+            .get("path")
+            .and_then(|path| path.as_str())
+            .ok_or_else(|| tera::Error::msg("asset() needs a path"))?;
+        let fingerprinted = asset_urls
+            .get(path.trim_start_matches('/'))
+            .ok_or_else(|| tera::Error::msg(format!("asset() of missing file {}", path)))?;
+        Ok(tera::Value::String(format!("/{}", fingerprinted)))
+    });
+
+    let runtime_config = serde_json::json!({
+        "apiUrl": settings.api_url,
+        "environment": settings.environment,
+        "release": release,
+        "store": settings.store,
+    });
+    let mut context = TeraContext::new();
+    context.insert("store", &settings.store);
+    context.insert("store_name", &settings.store.name);
+    context.insert("api_url", &settings.api_url);
+    context.insert("environment", &settings.environment);
+    context.insert("release", release);
+    context.insert("runtime_config", &script_safe_json(&runtime_config));
+
+    let mut pages = HashMap::new();
+    for (name, _) in templates.iter().filter(|(name, _)| !is_partial(name)) {
+        let html = tera
+            .render(name, &context)
+            .map_err(|e| anyhow::Error::new(e).context(format!("Failed to render {}", name)))?;
+        pages.insert(name.clone(), html);
+    }
+    Ok(pages)
+}
+
+/// Release names are plain directory names
+pub fn is_valid_release_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && !id.starts_with('.')
+        && id != CURRENT_LINK
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// The live release and the deploy history
+pub struct Hosting {
+    releases_dir: PathBuf,
+    settings: TemplateSettings,
+    spa_fallback: bool,
+    current: RwLock<Option<Arc<Release>>>,
+    /// Held through a deploy or rollback so they run one at a time
+    deploying: tokio::sync::Mutex<()>,
+}
+
+/// Result of a deploy or rollback
+#[derive(Debug, serde::Serialize)]
+pub struct DeployReport {
+    pub release: String,
+    pub previous: Option<String>,
+    pub pages: usize,
+    pub assets: usize,
+}
+
+impl Hosting {
+    /// Open `releases_dir`, loading the release that was live before a restart
+    pub fn open(config: &Config, releases_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(releases_dir)
+            .with_context(|| format!("Failed to create {}", releases_dir.display()))?;
+        let hosting = Self {
+            releases_dir: releases_dir.to_path_buf(),
+            settings: TemplateSettings::from_config(config),
+            spa_fallback: config.spa_fallback,
+            current: RwLock::new(None),
+            deploying: tokio::sync::Mutex::new(()),
+        };
+
+        let link = releases_dir.join(CURRENT_LINK);
+        let live = std::fs::read_link(&link)
+            .ok()
+            .and_then(|target| target.file_name()?.to_str().map(str::to_string));
+        match live {
+            Some(id) if is_valid_release_id(&id) => {
+                let release = Release::load(&id, &releases_dir.join(&id), &hosting.settings)
+                    .with_context(|| format!("Failed to load live release {}", id))?;
+                info!("Serving release {} ({} pages, {} assets)", id, release.pages.len(), release.assets.len());
+                *hosting.current.write().unwrap() = Some(Arc::new(release));
+            }
+            _ => warn!("No release deployed yet in {}", releases_dir.display()),
+        }
+        Ok(hosting)
+    }
+
+    pub fn current(&self) -> Option<Arc<Release>> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Make a release live
+    pub async fn deploy(&self, id: &str) -> Result<DeployReport> {
+        let _deploying = self.deploying.lock().await;
+        let mut history = self.history();
+        let report = self.activate(id).await?;
+        history.push(id.to_string());
+        self.save_history(&history)?;
+        Ok(report)
+    }
+
+    /// Go back to the release before the live one, or to a named one
+    pub async fn rollback(&self, target: Option<&str>) -> Result<DeployReport> {
+        let _deploying = self.deploying.lock().await;
+        let mut history = self.history();
+        let report = match target {
+            Some(id) => {
+                let report = self.activate(id).await?;
+                history.push(id.to_string());
+                report
+            }
+            None => {
+                if history.len() < 2 {
+                    bail!("No earlier release to roll back to");
+                }
+                let previous = history[history.len() - 2].clone();
+                let report = self.activate(&previous).await?;
+                history.pop();
+                report
+            }
+        };
+        self.save_history(&history)?;
+        Ok(report)
+    }
+
+    /// Releases on disk, newest first
+    pub fn releases(&self) -> Result<Vec<String>> {
+        let mut releases: Vec<(std::time::SystemTime, String)> = std::fs::read_dir(&self.releases_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .filter_map(|entry| {
+                let id = entry.file_name().to_str()?.to_string();
+                let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+                is_valid_release_id(&id).then_some((modified, id))
+            })
+            .collect();
+        releases.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(releases.into_iter().map(|(_, id)| id).collect())
+    }
+
+    /// Load a release, then swap it in and point the `current` link at it
+    async fn activate(&self, id: &str) -> Result<DeployReport> {
+        if !is_valid_release_id(id) {
+            bail!("Invalid release name {}", id);
+        }
+        let root = self.releases_dir.join(id);
+        if !root.is_dir() {
+            bail!("Release {} not found in {}", id, self.releases_dir.display());
+        }
+
+        let settings = self.settings.clone();
+        let release_id = id.to_string();
+        let release = tokio::task::spawn_blocking(move || Release::load(&release_id, &root, &settings))
+            .await
+            .context("Release loading panicked")??;
+        let report = DeployReport {
+            release: id.to_string(),
+            previous: self.current().map(|current| current.id.clone()),
+            pages: release.pages.len(),
+            assets: release.assets.len(),
+        };
+
+        *self.current.write().unwrap() = Some(Arc::new(release));
+        self.point_current_link(id)?;
+        info!(
+            "Release {} is live ({} pages, {} assets; was {})",
+            id,
+            report.pages,
+            report.assets,
+            report.previous.as_deref().unwrap_or("none")
+        );
+        Ok(report)
+    }
+
+    /// Replace the `current` symlink in one rename
+    fn point_current_link(&self, id: &str) -> Result<()> {
+        let temporary = self.releases_dir.join(".current.tmp");
+        let _ = std::fs::remove_file(&temporary);
+        std::os::unix::fs::symlink(id, &temporary).context("Failed to create release link")?;
+        std::fs::rename(&temporary, self.releases_dir.join(CURRENT_LINK)).context("Failed to switch release link")?;
+        Ok(())
+    }
+
+    fn history(&self) -> Vec<String> {
+        let mut history: Vec<String> = std::fs::read_to_string(self.releases_dir.join(HISTORY_FILE))
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| is_valid_release_id(line))
+            .map(str::to_string)
+            .collect();
+        if history.is_empty() {
+            if let Some(current) = self.current() {
+                history.push(current.id.clone());
+            }
+        }
+        history
+    }
+
+    fn save_history(&self, history: &[String]) -> Result<()> {
+        let temporary = self.releases_dir.join(".history.tmp");
+        std::fs::write(&temporary, history.join("\n") + "\n").context("Failed to write deploy history")?;
+        std::fs::rename(&temporary, self.releases_dir.join(HISTORY_FILE)).context("Failed to write deploy history")?;
+        Ok(())
+    }
+}
+
+/// The live release's response for a path, or None when it has nothing there
+pub async fn serve(hosting: &Hosting, path: &str) -> Option<Response> {
+    let Some(release) = hosting.current() else {
+        return Some((StatusCode::SERVICE_UNAVAILABLE, "No storefront release deployed").into_response());
+    };
+    let path = path.trim_start_matches('/');
+    if path.split('/').any(|segment| segment == ".." || segment.starts_with('.')) {
+        return None;
+    }
+
+    match release.resolve(path, hosting.spa_fallback)? {
+        Resolved::Page(html) => Some(
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+                .header(header::CACHE_CONTROL, "no-cache")
+                .header("X-Release", release.id.as_str())
+                .body(Body::from(html.to_string()))
+                .unwrap(),
+        ),
+        Resolved::Asset { path, immutable } => {
+            let content = tokio::fs::read(&path).await.ok()?;
+            let content_type = mime_guess::from_path(&path).first_or_octet_stream().to_string();
+            let cache_control = if immutable {
+                "public, max-age=31536000, immutable"
+            } else {
+                "no-cache"
+            };
+            Some(
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, content_type)
+                    .header(header::CACHE_CONTROL, cache_control)
+                    .body(Body::from(content))
+                    .unwrap(),
+            )
+        }
+    }
+}
+
+/// Deploy webhook request
+#[derive(Debug, Default, Deserialize)]
+pub struct DeployRequest {
+    pub release: Option<String>,
+}
+
+/// Deploy webhook routes for build pipelines; off without a `deploy_token`
+pub fn deploy_router() -> Router<AppState> {
+    Router::new()
+        .route("/_deploy", post(deploy))
+        .route("/_deploy/rollback", post(rollback))
+        .route("/_deploy/releases", get(releases))
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<Arc<Hosting>, StatusCode> {
+    let (Some(hosting), Some(expected)) = (state.hosting.clone(), state.config.deploy_token.as_deref()) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        warn!("Deploy webhook called with a wrong token");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(hosting)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn deploy_error(e: anyhow::Error) -> Response {
+    warn!("Deploy failed: {:#}", e);
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "error": format!("{:#}", e) }))).into_response()
+}
+
+/// `POST /_deploy` with `{"release": "<directory>"}`
+async fn deploy(State(state): State<AppState>, headers: HeaderMap, body: Option<Json<DeployRequest>>) -> Response {
+    let hosting = match authorize(&state, &headers) {
+        Ok(hosting) => hosting,
+        Err(status) => return status.into_response(),
+    };
+    let Some(release) = body.and_then(|Json(body)| body.release) else {
+        return (StatusCode::BAD_REQUEST, "release is required").into_response();
+    };
+    match hosting.deploy(&release).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => deploy_error(e),
+    }
+}
+
+/// `POST /_deploy/rollback`, optionally with `{"release": "<directory>"}`
+async fn rollback(State(state): State<AppState>, headers: HeaderMap, body: Option<Json<DeployRequest>>) -> Response {
+    let hosting = match authorize(&state, &headers) {
+        Ok(hosting) => hosting,
+        Err(status) => return status.into_response(),
+    };
+    let target = body.and_then(|Json(body)| body.release);
+    match hosting.rollback(target.as_deref()).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => deploy_error(e),
+    }
+}
+
+/// `GET /_deploy/releases`: the live release and those on disk
+async fn releases(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let hosting = match authorize(&state, &headers) {
+        Ok(hosting) => hosting,
+        Err(status) => return status.into_response(),
+    };
+    let current = hosting.current();
+    match hosting.releases() {
+        Ok(releases) => Json(serde_json::json!({
+            "current": current.as_ref().map(|release| &release.id),
+            "activated_at": current.as_ref().map(|release| release.activated_at),
+            "history": hosting.history(),
+            "releases": releases,
+        }))
+        .into_response(),
+        Err(e) => deploy_error(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> TemplateSettings {
+        TemplateSettings {
+            store: StoreSettings::default(),
+            api_url: "https://api.example.com".to_string(),
+            environment: "staging".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fingerprinted_path() {
+        let path = fingerprinted_path("css/app.css", b"body{}");
+        assert!(path.starts_with("css/app.") && path.ends_with(".css"));
+        assert_eq!(path.len(), "css/app..css".len() + FINGERPRINT_LENGTH);
+        assert_ne!(path, fingerprinted_path("css/app.css", b"body{color:red}"));
+        assert!(fingerprinted_path("LICENSE", b"MIT").starts_with("LICENSE."));
+    }
+
+    #[test]
+    fn test_release_ids() {
+        assert!(is_valid_release_id("2026-10-16_a1b2c3"));
+        assert!(!is_valid_release_id("current"));
+        assert!(!is_valid_release_id("../etc"));
+        assert!(!is_valid_release_id(".hidden"));
+        assert!(!is_valid_release_id(""));
+    }
+
+    #[test]
+    fn test_render_pages() {
+        let assets = HashMap::from([("app.js".to_string(), "app.0123456789.js".to_string())]);
+        let templates = vec![
+            ("_layout.html".to_string(), "<title>{{ store.name }}</title>{% block body %}{% endblock %}".to_string()),
+            (
+                "index.html".to_string(),
+                r#"{% extends "_layout.html" %}{% block body %}<script src="{{ asset(path='app.js') }}"></script><script>window.RC = {{ runtime_config | safe }}</script>{% endblock %}"#.to_string(),
+            ),
+        ];
+        let pages = render_pages("r1", templates, &assets, &settings()).unwrap();
+        assert_eq!(pages.len(), 1, "partials are not served");
+        let index = &pages["index.html"];
+        assert!(index.contains("<title>My Store</title>"));
+        assert!(index.contains(r#"src="/app.0123456789.js""#));
+        assert!(index.contains(r#""apiUrl":"https://api.example.com""#));
+
+        // A reference to a missing asset fails the deploy
+        let broken = vec![("index.html".to_string(), "{{ asset(path='missing.css') }}".to_string())];
+        assert!(render_pages("r1", broken, &assets, &settings()).is_err());
+    }
+
+    #[test]
+    fn test_script_safe_json() {
+        let json = script_safe_json(&serde_json::json!({ "name": "</script><b>" }));
+        assert!(!json.contains('<') && !json.contains('>'));
+    }
+}
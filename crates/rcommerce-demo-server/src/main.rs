@@ -6,6 +6,10 @@
 //! - API data fetching with caching
 //! - Hot reload in development mode
 //! - Edge caching headers for CloudFlare/CDN
+//! - Redirects (old slugs, manual redirects) looked up before answering 404
+//! - API Cache-Control hints: TTLs per route, private responses not cached
+//! - Stale API responses revalidated by ETag instead of downloaded again
+//! - Hosting mode: fingerprinted storefront builds deployed atomically by webhook
 
 use anyhow::Result;
 use axum::{
@@ -34,6 +38,9 @@ use chrono::{Datelike, Utc};
 mod cache;
 use cache::{Cache, CacheBackend};
 
+mod hosting;
+use hosting::Hosting;
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Config {
     #[serde(default = "default_bind")]
@@ -61,6 +68,44 @@ pub struct Config {
     
     #[serde(default = "default_log_level")]
     pub log_level: String,
+    
+    /// Store settings available to every template as `store`
+    #[serde(default)]
+    pub store: StoreSettings,
+    
+    /// API endpoint injected into pages for browsers (the proxy by default)
+    #[serde(default = "default_public_api_url")]
+    pub public_api_url: String,
+    
+    #[serde(default = "default_environment")]
+    pub environment: String,
+    
+    /// Serve storefront builds from here instead of the SSR routes
+    #[serde(default)]
+    pub releases_dir: Option<PathBuf>,
+    
+    /// Bearer token of the deploy webhook; the webhook is off without one
+    #[serde(default)]
+    pub deploy_token: Option<String>,
+    
+    /// Answer unknown extensionless paths with index.html (single-page apps)
+    #[serde(default)]
+    pub spa_fallback: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct StoreSettings {
+    #[serde(default = "default_store_name")]
+    pub name: String,
+    /// Any other settings, e.g. `currency` or `support_email`
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self { name: default_store_name(), extra: HashMap::new() }
+    }
 }
 
 fn default_bind() -> String { "0.0.0.0:3000".to_string() }
@@ -69,6 +114,9 @@ fn default_static_dir() -> PathBuf { PathBuf::from("static") }
 fn default_cache_ttl() -> u64 { 300 }
 fn default_rate_limit() -> u32 { 60 }
 fn default_log_level() -> String { "info".to_string() }
+fn default_store_name() -> String { "My Store".to_string() }
+fn default_public_api_url() -> String { "/api".to_string() }
+fn default_environment() -> String { "production".to_string() }
 
 impl Config {
     pub fn load(args: &Args) -> Result<Self> {
@@ -80,6 +128,8 @@ impl Config {
         config = config.set_default("rate_limit_per_minute", default_rate_limit() as i64)?;
         config = config.set_default("dev_mode", false)?;
         config = config.set_default("log_level", default_log_level())?;
+        config = config.set_default("public_api_url", default_public_api_url())?;
+        config = config.set_default("environment", default_environment())?;
         
         if let Some(ref path) = args.config {
             config = config.add_source(config::File::from(path.as_path()));
@@ -90,6 +140,8 @@ impl Config {
         if let Some(ref bind) = args.bind { config = config.set_override("bind", bind.as_str())?; }
         if let Some(ref api_url) = args.api_url { config = config.set_override("api_url", api_url.as_str())?; }
         if let Some(ref api_key) = args.api_key { config = config.set_override("api_key", api_key.as_str())?; }
+        if let Some(ref url) = args.public_api_url { config = config.set_override("public_api_url", url.as_str())?; }
+        if let Some(ref environment) = args.environment { config = config.set_override("environment", environment.as_str())?; }
         
         let config: Config = config.build()?.try_deserialize()?;
         config.validate()?;
@@ -106,6 +158,12 @@ impl Config {
         if !self.static_dir.exists() {
             std::fs::create_dir_all(&self.static_dir)?;
         }
+        if self.deploy_token.as_ref().is_some_and(|token| token.len() < 32) {
+            anyhow::bail!("Deploy token must be at least 32 characters");
+        }
+        if self.deploy_token.is_some() && self.releases_dir.is_none() {
+            warn!("deploy_token is set but releases_dir isn't; the deploy webhook is off");
+        }
         Ok(())
     }
 }
@@ -118,6 +176,7 @@ pub struct AppState {
     pub cache: Arc<Cache>,
     pub tera: Arc<RwLock<Tera>>,
     pub rate_limiter: Arc<RwLock<RateLimiter>>,
+    pub hosting: Option<Arc<Hosting>>,
 }
 
 pub struct RateLimiter {
@@ -155,6 +214,10 @@ pub struct Args {
     pub api_url: Option<String>,
     #[arg(short = 'k', long, env = "FRONTEND_API_KEY")]
     pub api_key: Option<String>,
+    #[arg(long, env = "FRONTEND_PUBLIC_API_URL")]
+    pub public_api_url: Option<String>,
+    #[arg(long, env = "FRONTEND_ENVIRONMENT")]
+    pub environment: Option<String>,
 }
 
 // Route parameters
@@ -174,6 +237,14 @@ async fn main() -> Result<()> {
     info!("R Commerce Frontend Server v{}", env!("CARGO_PKG_VERSION"));
     info!("Mode: {}", if config.dev_mode { "DEVELOPMENT" } else { "PRODUCTION" });
     
+    let hosting = match &config.releases_dir {
+        Some(dir) => {
+            info!("Hosting storefront releases from {}", dir.display());
+            Some(Arc::new(Hosting::open(&config, dir)?))
+        }
+        None => None,
+    };
+    
     // Initialize Tera template engine
     let template_glob = format!("{}/**/*.html", config.template_dir.display());
     let tera = match Tera::new(&template_glob) {
@@ -207,22 +278,20 @@ async fn main() -> Result<()> {
         cache: Arc::new(Cache::new(cache_backend)),
         tera: Arc::new(RwLock::new(tera)),
         rate_limiter: Arc::new(RwLock::new(RateLimiter::new(config.rate_limit_per_minute))),
+        hosting: hosting.clone(),
     };
     
-    // Build router with dynamic routes
-    let app = Router::new()
-        // Health check
-        .route("/health", get(health_check))
-        // Dynamic routes
-        .route("/", get(home_page))
-        .route("/products/:id", get(product_page))
-        .route("/categories/:slug", get(category_page))
-        .route("/pages/:slug", get(cms_page))
-        .route("/cart", get(cart_page))
-        // API proxy
-        .route("/api/*path", get(api_proxy))
-        // Static files
-        .route("/static/*path", get(static_files))
+    // Hosting mode: the live release answers everything but health, API and deploys
+    let router = if hosting.is_some() {
+        Router::new()
+            .route("/health", get(health_check))
+            .route("/api/*path", get(api_proxy))
+            .merge(hosting::deploy_router())
+            .fallback(hosted_page)
+    } else {
+        dynamic_routes()
+    };
+    let app = router
         .with_state(state)
         .layer(axum::middleware::from_fn(security_headers));
     
@@ -237,6 +306,24 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+// Build router with dynamic routes
+fn dynamic_routes() -> Router<AppState> {
+    Router::new()
+        // Health check
+        .route("/health", get(health_check))
+        // Dynamic routes
+        .route("/", get(home_page))
+        .route("/products/:id", get(product_page))
+        .route("/categories/:slug", get(category_page))
+        .route("/pages/:slug", get(cms_page))
+        .route("/cart", get(cart_page))
+        // API proxy
+        .route("/api/*path", get(api_proxy))
+        // Static files
+        .route("/static/*path", get(static_files))
+        .fallback(not_found)
+}
+
 // Handler: Home page
 async fn home_page(State(state): State<AppState>) -> Result<Response, StatusCode> {
     // Fetch featured products from API
@@ -255,11 +342,13 @@ async fn home_page(State(state): State<AppState>) -> Result<Response, StatusCode
 async fn product_page(
     State(state): State<AppState>,
     Path(params): Path<ProductParams>,
+    uri: Uri,
 ) -> Result<Response, StatusCode> {
     // Fetch product from API
-    let product = fetch_api(&state, &format!("/api/v1/products/{}", params.id))
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let product = match fetch_api(&state, &format!("/api/v1/products/{}", params.id)).await {
+        Ok(product) => product,
+        Err(_) => return Ok(not_found(State(state), uri).await),
+    };
     
     let mut ctx = TeraContext::new();
     ctx.insert("title", &product["name"].as_str().unwrap_or("Product"));
@@ -319,7 +408,9 @@ async fn render_template(
     mut ctx: TeraContext,
 ) -> Result<Response, StatusCode> {
     // Add global context
-    ctx.insert("store_name", "My Store");
+    ctx.insert("store", &state.config.store);
+    ctx.insert("store_name", &state.config.store.name);
+    ctx.insert("environment", &state.config.environment);
     ctx.insert("current_year", &Utc::now().year());
     let tera = state.tera.read().await;
     
@@ -343,41 +434,97 @@ async fn render_template(
     }
 }
 
-// Helper: Fetch from API with caching
+// Helper: Fetch from API with caching; stale responses with an ETag are
+// revalidated, so an unchanged one isn't downloaded again
 async fn fetch_api(state: &AppState, path: &str) -> Result<serde_json::Value, anyhow::Error> {
     let cache_key = format!("api:{}", path);
     
     // Try cache
-    if let Ok(Some(cached)) = state.cache.get(&cache_key).await {
+    let cached = state.cache.get(&cache_key).await.ok().flatten();
+    if let Some(cached) = cached.as_ref().filter(|cached| cached.is_fresh()) {
         return Ok(serde_json::from_slice(&cached.body)?);
     }
     
     // Fetch from API
     let url = format!("{}{}", state.config.api_url, path);
-    let response = state.http_client
+    let mut request = state.http_client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", state.config.api_key))
-        .send()
-        .await?;
+        .header("Authorization", format!("Bearer {}", state.config.api_key));
+    if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_deref()) {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await?;
+    
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(mut cached) = cached {
+            if let Some(ttl_secs) = cache_ttl_hint(response.headers(), state.config.cache_ttl_secs) {
+                cached.cached_at = chrono::Utc::now();
+                cached.fresh_secs = ttl_secs;
+                let _ = state.cache.set(&cache_key, &cached, cached.keep_secs()).await;
+            }
+            return Ok(serde_json::from_slice(&cached.body)?);
+        }
+    }
     
     if !response.status().is_success() {
         anyhow::bail!("API error: {}", response.status());
     }
     
+    let ttl_secs = cache_ttl_hint(response.headers(), state.config.cache_ttl_secs);
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
     let data: serde_json::Value = response.json().await?;
+    let Some(ttl_secs) = ttl_secs else {
+        return Ok(data);
+    };
     
-    // Cache the response
+    // Cache the response for as long as the API allows
     let cached = cache::CachedResponse {
         body: serde_json::to_vec(&data)?,
         content_type: "application/json".to_string(),
         cached_at: chrono::Utc::now(),
+        etag,
+        fresh_secs: ttl_secs,
         backend: String::new(),
     };
-    let _ = state.cache.set(&cache_key, &cached, state.config.cache_ttl_secs).await;
+    let _ = state.cache.set(&cache_key, &cached, cached.keep_secs()).await;
     
     Ok(data)
 }
 
+// Helper: How long the API's Cache-Control lets a shared cache keep a
+// response; None for private and no-store responses, the default without a hint
+fn cache_ttl_hint(headers: &reqwest::header::HeaderMap, default_ttl_secs: u64) -> Option<u64> {
+    let Some(cache_control) = headers
+        .get(reqwest::header::CACHE_CONTROL)
+        .and_then(|h| h.to_str().ok())
+    else {
+        return Some(default_ttl_secs);
+    };
+    
+    let mut max_age = None;
+    for directive in cache_control.split(',').map(|d| d.trim().to_ascii_lowercase()) {
+        match directive.as_str() {
+            "private" | "no-store" | "no-cache" => return None,
+            _ => {
+                if let Some(secs) = directive.strip_prefix("s-maxage=").and_then(|s| s.parse().ok()) {
+                    max_age = Some(secs);
+                } else if let Some(secs) = directive.strip_prefix("max-age=").and_then(|s| s.parse().ok()) {
+                    max_age = max_age.or(Some(secs));
+                }
+            }
+        }
+    }
+    match max_age {
+        Some(0) => None,
+        Some(secs) => Some(secs),
+        None => Some(default_ttl_secs),
+    }
+}
+
 // Handler: API proxy
 async fn api_proxy(
     State(state): State<AppState>,
@@ -409,13 +556,23 @@ async fn api_proxy(
         .map_err(|_| StatusCode::BAD_GATEWAY)?;
     
     let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::OK);
+    // Pass the API's caching hints on to the CDN
+    let hints: Vec<(&str, String)> = ["Cache-Control", "Surrogate-Key"]
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.headers().get(name)?.to_str().ok()?;
+            Some((name, value.to_string()))
+        })
+        .collect();
     let body = response.bytes().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
     
-    Ok(Response::builder()
+    let mut builder = Response::builder()
         .status(status)
-        .header("Content-Type", "application/json")
-        .body(Body::from(body))
-        .unwrap())
+        .header("Content-Type", "application/json");
+    for (name, value) in hints {
+        builder = builder.header(name, value);
+    }
+    Ok(builder.body(Body::from(body)).unwrap())
 }
 
 // Handler: Static files
@@ -460,6 +617,53 @@ async fn static_files(
     }
 }
 
+// Handler: Pages and assets of the live release in hosting mode
+async fn hosted_page(State(state): State<AppState>, uri: Uri) -> Response {
+    if let Some(hosting) = &state.hosting {
+        if let Some(response) = hosting::serve(hosting, uri.path()).await {
+            return response;
+        }
+    }
+    not_found(State(state), uri).await
+}
+
+// Handler: Unknown paths redirect if the API knows where they moved
+async fn not_found(State(state): State<AppState>, uri: Uri) -> Response {
+    if let Some(response) = resolve_redirect(&state, uri.path()).await {
+        return response;
+    }
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::from("Not found"))
+        .unwrap()
+}
+
+// Helper: Redirect for a path that moved (not cached here, so the API counts hits)
+async fn resolve_redirect(state: &AppState, path: &str) -> Option<Response> {
+    let url = format!("{}/api/v1/redirects/resolve", state.config.api_url);
+    let response = state.http_client
+        .get(&url)
+        .query(&[("path", path)])
+        .header("Authorization", format!("Bearer {}", state.config.api_key))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    
+    let resolution: serde_json::Value = response.json().await.ok()?;
+    let location = resolution["location"].as_str()?;
+    let status = resolution["status_code"].as_u64()
+        .and_then(|code| StatusCode::from_u16(code as u16).ok())
+        .filter(StatusCode::is_redirection)?;
+    Response::builder()
+        .status(status)
+        .header("Location", location)
+        .body(Body::empty())
+        .ok()
+}
+
 async fn health_check() -> impl IntoResponse {
     axum::Json(serde_json::json!({
         "status": "ok",